@@ -34,13 +34,16 @@ import {
 interface HighlightRange {
   start: number;
   end: number;
+  // Character offsets; start/end are bytes, which differ for non-ASCII text
+  start_char?: number;
+  end_char?: number;
 }
 
 interface Snippet {
   text: string;
   highlight_ranges?: HighlightRange[];
   source?: 'content' | 'ocr_text' | 'filename';
-  page_number?: number;
+  page_number?: number | null;
   confidence?: number;
 }
 
@@ -94,6 +97,11 @@ const EnhancedSnippetViewer: React.FC<EnhancedSnippetViewerProps> = ({
     setTimeout(() => setCopiedIndex(null), 2000);
   };
 
+  const charRange = (range: HighlightRange) => ({
+    start: range.start_char ?? range.start,
+    end: range.end_char ?? range.end,
+  });
+
   const renderHighlightedText = (text: string, highlightRanges?: HighlightRange[]): React.ReactNode => {
     if (!highlightRanges || highlightRanges.length === 0) {
       return text;
@@ -102,7 +110,7 @@ const EnhancedSnippetViewer: React.FC<EnhancedSnippetViewerProps> = ({
     const parts: React.ReactNode[] = [];
     let lastIndex = 0;
 
-    highlightRanges.forEach((range, index) => {
+    highlightRanges.map(charRange).forEach((range, index) => {
       // Add text before highlight
       if (range.start > lastIndex) {
         parts.push(
@@ -177,8 +185,8 @@ const EnhancedSnippetViewer: React.FC<EnhancedSnippetViewerProps> = ({
     // Extract context around highlights if in context mode
     let displayText = snippet.text;
     if (showContext && snippet.highlight_ranges && snippet.highlight_ranges.length > 0) {
-      const firstHighlight = snippet.highlight_ranges[0];
-      const lastHighlight = snippet.highlight_ranges[snippet.highlight_ranges.length - 1];
+      const firstHighlight = charRange(snippet.highlight_ranges[0]);
+      const lastHighlight = charRange(snippet.highlight_ranges[snippet.highlight_ranges.length - 1]);
       
       const contextStart = Math.max(0, firstHighlight.start - contextLength);
       const contextEnd = Math.min(snippet.text.length, lastHighlight.end + contextLength);
@@ -192,7 +200,7 @@ const EnhancedSnippetViewer: React.FC<EnhancedSnippetViewerProps> = ({
         snippet = {
           ...snippet,
           text: displayText,
-          highlight_ranges: snippet.highlight_ranges.map(range => ({
+          highlight_ranges: snippet.highlight_ranges.map(charRange).map(range => ({
             start: range.start - contextStart + (contextStart > 0 ? 3 : 0),
             end: range.end - contextStart + (contextStart > 0 ? 3 : 0),
           })),
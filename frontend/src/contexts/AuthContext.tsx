@@ -59,6 +59,8 @@ export function AuthProvider({ children }: { children: React.ReactNode }) {
   }
 
   const logout = () => {
+    // End the session on the server too; the local logout does not wait for it
+    api.post('/auth/logout').catch(() => {})
     localStorage.removeItem('token')
     delete api.defaults.headers.common['Authorization']
     setUser(null)
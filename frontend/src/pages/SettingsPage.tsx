@@ -208,7 +208,7 @@ const SettingsPage: React.FC = () => {
     enableImagePreprocessing: false,
     searchResultsPerPage: 25,
     searchSnippetLength: 200,
-    fuzzySearchThreshold: 0.8,
+    fuzzySearchThreshold: 0.5,
     retentionDays: null,
     enableAutoCleanup: false,
     enableCompression: false,
@@ -313,7 +313,7 @@ const SettingsPage: React.FC = () => {
         enableImagePreprocessing: response.data.enable_image_preprocessing !== undefined ? response.data.enable_image_preprocessing : false,
         searchResultsPerPage: response.data.search_results_per_page || 25,
         searchSnippetLength: response.data.search_snippet_length || 200,
-        fuzzySearchThreshold: response.data.fuzzy_search_threshold || 0.8,
+        fuzzySearchThreshold: response.data.fuzzy_search_threshold || 0.5,
         retentionDays: response.data.retention_days,
         enableAutoCleanup: response.data.enable_auto_cleanup || false,
         enableCompression: response.data.enable_compression || false,
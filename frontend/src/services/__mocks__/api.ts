@@ -256,12 +256,18 @@ export interface SearchRequest {
 export interface HighlightRange {
   start: number
   end: number
+  start_char: number
+  end_char: number
 }
 
 export interface SearchSnippet {
   text: string
   start_offset: number
   end_offset: number
+  start_char: number
+  end_char: number
+  source: 'content' | 'ocr_text'
+  page_number?: number | null
   highlight_ranges: HighlightRange[]
 }
 
@@ -287,6 +293,13 @@ export interface SearchResponse {
   total: number
   query_time_ms: number
   suggestions: string[]
+  facets?: {
+    labels: { value: string; count: number }[]
+    mime_types: { value: string; count: number }[]
+    sources: { value: string; count: number }[]
+    correspondents: { value: string; count: number }[]
+    years: { value: string; count: number }[]
+  }
 }
 
 export default api
\ No newline at end of file
@@ -76,17 +76,28 @@ export interface SearchRequest {
   include_snippets?: boolean
   snippet_length?: number
   search_mode?: 'simple' | 'phrase' | 'fuzzy' | 'boolean'
+  include_facets?: boolean
 }
 
 export interface HighlightRange {
+  /** Byte offsets within the snippet */
   start: number
   end: number
+  /** Character offsets within the snippet */
+  start_char: number
+  end_char: number
 }
 
 export interface SearchSnippet {
   text: string
+  /** Byte offsets in the searched text */
   start_offset: number
   end_offset: number
+  /** Character offsets in the searched text */
+  start_char: number
+  end_char: number
+  source: 'content' | 'ocr_text'
+  page_number?: number | null
   highlight_ranges: HighlightRange[]
 }
 
@@ -125,11 +136,19 @@ export interface EnhancedDocument {
   snippets: SearchSnippet[]
 }
 
+export interface DocumentSearchResponse {
+  document_id: string
+  query: string
+  total: number
+  matches: SearchSnippet[]
+}
+
 export interface SearchResponse {
   documents: EnhancedDocument[]
   total: number
   query_time_ms: number
   suggestions: string[]
+  facets?: SearchResultFacets
 }
 
 export interface FacetItem {
@@ -137,6 +156,14 @@ export interface FacetItem {
   count: number
 }
 
+export interface SearchResultFacets {
+  labels: FacetItem[]
+  mime_types: FacetItem[]
+  sources: FacetItem[]
+  correspondents: FacetItem[]
+  years: FacetItem[]
+}
+
 export interface SearchFacetsResponse {
   mime_types: FacetItem[]
   tags: FacetItem[]
@@ -334,6 +361,12 @@ export const documentService = {
     return api.get<OcrResponse>(`/documents/${id}/ocr`)
   },
 
+  searchWithin: (id: string, q: string, params?: { limit?: number; offset?: number; snippet_length?: number }) => {
+    return api.get<DocumentSearchResponse>(`/documents/${id}/search`, {
+      params: { q, ...params },
+    })
+  },
+
   view: (id: string) => {
     return api.get(`/documents/${id}/view`, {
       responseType: 'blob',
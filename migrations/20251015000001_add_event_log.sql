@@ -0,0 +1,19 @@
+-- Event log backing versioned webhook/event deliveries
+-- Every event is persisted with its schema version so it can be replayed later
+
+CREATE TABLE IF NOT EXISTS events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event_type TEXT NOT NULL,
+    schema_version INTEGER NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    resource_id UUID,
+    payload JSONB NOT NULL DEFAULT '{}'::jsonb,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_occurred_at ON events(occurred_at);
+CREATE INDEX IF NOT EXISTS idx_events_type_occurred_at ON events(event_type, occurred_at);
+CREATE INDEX IF NOT EXISTS idx_events_user_id ON events(user_id);
+
+COMMENT ON TABLE events IS 'Append-only log of versioned events delivered to webhooks and event consumers';
+COMMENT ON COLUMN events.schema_version IS 'Version of the payload schema for this event type at the time it was emitted';
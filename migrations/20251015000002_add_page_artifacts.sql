@@ -0,0 +1,22 @@
+-- Page-level artifacts (stamps, signatures, embedded figures) detected on document pages
+-- Each artifact keeps its own OCR text and an optional vision-model description so
+-- stamped or signed copies can be located through search
+
+CREATE TABLE IF NOT EXISTS page_artifacts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_number INTEGER NOT NULL CHECK (page_number >= 1),
+    kind TEXT NOT NULL CHECK (kind IN ('stamp', 'signature', 'figure')),
+    bbox JSONB NOT NULL,
+    detection_score REAL NOT NULL DEFAULT 0,
+    ocr_text TEXT,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_artifacts_document_id ON page_artifacts(document_id);
+CREATE INDEX IF NOT EXISTS idx_page_artifacts_kind ON page_artifacts(kind);
+CREATE INDEX IF NOT EXISTS idx_page_artifacts_search ON page_artifacts
+    USING GIN (to_tsvector('english', COALESCE(ocr_text, '') || ' ' || COALESCE(description, '')));
+
+COMMENT ON COLUMN page_artifacts.bbox IS 'Pixel bounding box {x, y, width, height} on the rasterized page';
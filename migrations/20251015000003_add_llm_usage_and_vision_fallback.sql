@@ -0,0 +1,27 @@
+-- Metered LLM usage per user, used to enforce quotas on costly features
+CREATE TABLE IF NOT EXISTS llm_usage (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    feature TEXT NOT NULL,
+    units INTEGER NOT NULL CHECK (units > 0),
+    document_id UUID REFERENCES documents(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_llm_usage_user_feature_created ON llm_usage(user_id, feature, created_at);
+
+-- Pages whose text was transcribed by a vision LLM because OCR confidence was too low
+CREATE TABLE IF NOT EXISTS vision_fallback_pages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_number INTEGER NOT NULL CHECK (page_number >= 1),
+    model TEXT NOT NULL,
+    ocr_confidence REAL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (document_id, page_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_vision_fallback_pages_document_id ON vision_fallback_pages(document_id);
+
+COMMENT ON COLUMN llm_usage.units IS 'Feature-specific unit, e.g. pages sent for vision transcription';
+COMMENT ON COLUMN vision_fallback_pages.ocr_confidence IS 'Tesseract confidence of the document before the vision fallback replaced it';
@@ -0,0 +1,15 @@
+-- Documents split out of a single batch-scanned upload
+-- The original upload is kept; each child covers a page range of it
+
+CREATE TABLE IF NOT EXISTS document_splits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    parent_document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    child_document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    start_page INTEGER NOT NULL CHECK (start_page >= 1),
+    end_page INTEGER NOT NULL CHECK (end_page >= start_page),
+    method TEXT NOT NULL CHECK (method IN ('blank_page', 'separator_sheet', 'llm')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (child_document_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_splits_parent ON document_splits(parent_document_id);
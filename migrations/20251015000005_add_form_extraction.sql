@@ -0,0 +1,25 @@
+-- Key/value pairs extracted from structured forms, and the layouts learned from them
+
+CREATE TABLE IF NOT EXISTS form_templates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- Ordered list of {key, label} the layout is known to contain
+    fields JSONB NOT NULL DEFAULT '[]',
+    times_matched INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_form_templates_user_id ON form_templates(user_id);
+
+CREATE TABLE IF NOT EXISTS document_form_fields (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    template_id UUID REFERENCES form_templates(id) ON DELETE SET NULL,
+    -- Map of normalized key to extracted value (null when the template field was not found)
+    fields JSONB NOT NULL DEFAULT '{}',
+    extracted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_form_fields_template_id ON document_form_fields(template_id);
+CREATE INDEX IF NOT EXISTS idx_document_form_fields_fields ON document_form_fields USING GIN (fields);
@@ -0,0 +1,15 @@
+-- Attachments extracted from ingested emails (.eml/.msg)
+-- Each attachment is a document of its own, linked to the email it came from
+
+CREATE TABLE IF NOT EXISTS document_attachments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    parent_document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    child_document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    position INTEGER NOT NULL CHECK (position >= 1),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (parent_document_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_attachments_parent ON document_attachments(parent_document_id);
+CREATE INDEX IF NOT EXISTS idx_document_attachments_child ON document_attachments(child_document_id);
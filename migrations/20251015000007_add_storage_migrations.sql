@@ -0,0 +1,41 @@
+-- Guided moves of stored document files between storage backends and path layouts
+-- Each document is copied, verified by hash and only then switched to its new path;
+-- the per-document items make a run resumable and feed the reconciliation report
+
+CREATE TABLE IF NOT EXISTS storage_migrations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    target_backend TEXT NOT NULL CHECK (target_backend IN ('local', 's3')),
+    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'failed')),
+    batch_size INTEGER NOT NULL CHECK (batch_size > 0),
+    delete_source BOOLEAN NOT NULL DEFAULT FALSE,
+    total_documents BIGINT NOT NULL DEFAULT 0,
+    migrated_documents BIGINT NOT NULL DEFAULT 0,
+    skipped_documents BIGINT NOT NULL DEFAULT 0,
+    failed_documents BIGINT NOT NULL DEFAULT 0,
+    -- Documents are processed in id order; a resumed run continues after this one
+    last_document_id UUID,
+    report JSONB,
+    error TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- At most one migration runs at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_storage_migrations_single_running
+    ON storage_migrations ((status)) WHERE status = 'running';
+
+CREATE TABLE IF NOT EXISTS storage_migration_items (
+    migration_id UUID NOT NULL REFERENCES storage_migrations(id) ON DELETE CASCADE,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    source_path TEXT NOT NULL,
+    target_path TEXT,
+    sha256 TEXT,
+    status TEXT NOT NULL CHECK (status IN ('migrated', 'failed')),
+    error TEXT,
+    source_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+    processed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (migration_id, document_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_storage_migration_items_status ON storage_migration_items(migration_id, status);
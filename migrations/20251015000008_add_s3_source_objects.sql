@@ -0,0 +1,13 @@
+-- Objects each S3 source has ingested, with the ETag they had at the time, so bucket
+-- notifications and polls skip keys whose content has not changed
+CREATE TABLE IF NOT EXISTS s3_source_objects (
+    source_id UUID NOT NULL REFERENCES sources(id) ON DELETE CASCADE,
+    object_key TEXT NOT NULL,
+    etag TEXT,
+    -- Deleting the document makes the object eligible for import again
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (source_id, object_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_s3_source_objects_document ON s3_source_objects(document_id);
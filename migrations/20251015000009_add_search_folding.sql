@@ -0,0 +1,135 @@
+-- Accent and case folding for full-text search. Each supported language gets a
+-- readur_<language> text search configuration that runs unaccent before the
+-- stemmer, so "resume" and "résumé" index to the same lexeme.
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+DO $$
+DECLARE
+    lang TEXT;
+BEGIN
+    FOREACH lang IN ARRAY ARRAY[
+        'english', 'german', 'french', 'spanish', 'italian', 'portuguese', 'dutch',
+        'danish', 'norwegian', 'swedish', 'finnish', 'hungarian', 'romanian', 'russian', 'turkish'
+    ] LOOP
+        IF NOT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = 'readur_' || lang) THEN
+            EXECUTE format('CREATE TEXT SEARCH CONFIGURATION readur_%s (COPY = %s)', lang, lang);
+            EXECUTE format(
+                'ALTER TEXT SEARCH CONFIGURATION readur_%s ALTER MAPPING FOR hword, hword_part, word WITH unaccent, %s_stem',
+                lang, lang
+            );
+        END IF;
+    END LOOP;
+
+    IF NOT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = 'readur_simple') THEN
+        CREATE TEXT SEARCH CONFIGURATION readur_simple (COPY = simple);
+        ALTER TEXT SEARCH CONFIGURATION readur_simple
+            ALTER MAPPING FOR hword, hword_part, word WITH unaccent, simple;
+    END IF;
+END $$;
+
+-- Text search configuration for a Tesseract language code ("deu", "eng+fra", ...).
+-- Combined codes use their first language; languages without a stemmer fold only.
+CREATE OR REPLACE FUNCTION readur_search_config(language_code TEXT)
+RETURNS regconfig AS $$
+    SELECT (CASE split_part(COALESCE(language_code, 'eng'), '+', 1)
+        WHEN 'eng' THEN 'readur_english'
+        WHEN 'deu' THEN 'readur_german'
+        WHEN 'fra' THEN 'readur_french'
+        WHEN 'spa' THEN 'readur_spanish'
+        WHEN 'ita' THEN 'readur_italian'
+        WHEN 'por' THEN 'readur_portuguese'
+        WHEN 'nld' THEN 'readur_dutch'
+        WHEN 'dan' THEN 'readur_danish'
+        WHEN 'nor' THEN 'readur_norwegian'
+        WHEN 'swe' THEN 'readur_swedish'
+        WHEN 'fin' THEN 'readur_finnish'
+        WHEN 'hun' THEN 'readur_hungarian'
+        WHEN 'ron' THEN 'readur_romanian'
+        WHEN 'rus' THEN 'readur_russian'
+        WHEN 'tur' THEN 'readur_turkish'
+        ELSE 'readur_simple'
+    END)::regconfig
+$$ LANGUAGE sql STABLE;
+
+-- German spells umlauts out as ae/oe/ue when they are unavailable, so German
+-- text is indexed under both "muller" (unaccented) and "mueller"
+CREATE OR REPLACE FUNCTION readur_expand_umlauts(input TEXT)
+RETURNS TEXT AS $$
+    SELECT replace(replace(replace(replace(replace(replace(input,
+        'ä', 'ae'), 'ö', 'oe'), 'ü', 'ue'), 'Ä', 'Ae'), 'Ö', 'Oe'), 'Ü', 'Ue')
+$$ LANGUAGE sql IMMUTABLE STRICT;
+
+CREATE OR REPLACE FUNCTION readur_search_vector(body TEXT, config regconfig)
+RETURNS tsvector AS $$
+    SELECT CASE
+        WHEN config = 'readur_german'::regconfig AND body ~ '[äöüÄÖÜ]'
+            THEN to_tsvector(config, body) || to_tsvector(config, readur_expand_umlauts(body))
+        ELSE to_tsvector(config, body)
+    END
+$$ LANGUAGE sql STABLE;
+
+-- Search vector of a document, using the language its owner OCRs in
+CREATE OR REPLACE FUNCTION readur_document_search_vector(owner_id UUID, content TEXT, ocr_text TEXT)
+RETURNS tsvector AS $$
+    SELECT readur_search_vector(
+        COALESCE(content, '') || ' ' || COALESCE(ocr_text, ''),
+        readur_search_config((
+            SELECT COALESCE(primary_language, ocr_language) FROM settings WHERE user_id = owner_id
+        ))
+    )
+$$ LANGUAGE sql STABLE;
+
+-- Configurations that documents are currently indexed with
+CREATE OR REPLACE FUNCTION readur_search_configs()
+RETURNS regconfig[] AS $$
+    SELECT array_agg(DISTINCT config) FROM (
+        SELECT 'readur_english'::regconfig AS config
+        UNION
+        SELECT readur_search_config(COALESCE(primary_language, ocr_language)) FROM settings
+    ) configs
+$$ LANGUAGE sql STABLE;
+
+-- Query text parsed with every configuration in use and OR-ed together, so a
+-- search matches documents regardless of which language rules indexed them.
+-- mode is 'plain', 'phrase' or 'boolean'.
+CREATE OR REPLACE FUNCTION readur_search_query(query_text TEXT, mode TEXT DEFAULT 'plain')
+RETURNS tsquery AS $$
+DECLARE
+    config regconfig;
+    part tsquery;
+    result tsquery;
+BEGIN
+    FOREACH config IN ARRAY readur_search_configs() LOOP
+        part := CASE mode
+            WHEN 'phrase' THEN phraseto_tsquery(config, query_text)
+            WHEN 'boolean' THEN to_tsquery(config, query_text)
+            ELSE plainto_tsquery(config, query_text)
+        END;
+        result := CASE WHEN result IS NULL THEN part ELSE result || part END;
+    END LOOP;
+    RETURN result;
+END;
+$$ LANGUAGE plpgsql STABLE;
+
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS search_vector tsvector;
+
+CREATE OR REPLACE FUNCTION update_document_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector := readur_document_search_vector(NEW.user_id, NEW.content, NEW.ocr_text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_document_search_vector ON documents;
+CREATE TRIGGER trigger_update_document_search_vector
+    BEFORE INSERT OR UPDATE OF content, ocr_text, user_id ON documents
+    FOR EACH ROW
+    EXECUTE FUNCTION update_document_search_vector();
+
+UPDATE documents SET search_vector = readur_document_search_vector(user_id, content, ocr_text);
+
+CREATE INDEX IF NOT EXISTS idx_documents_search_vector ON documents USING GIN(search_vector);
+
+-- Superseded by idx_documents_search_vector
+DROP INDEX IF EXISTS idx_documents_content_search;
@@ -0,0 +1,17 @@
+-- Webhook endpoints each receive the subset of their owner's events matching
+-- their filter, evaluated server-side before delivery
+CREATE TABLE IF NOT EXISTS webhook_endpoints (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    description TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- {"event_types": [...], "tags": [...], "source_ids": [...], "mime_types": [...]}; empty lists match everything
+    filter JSONB NOT NULL DEFAULT '{}'::jsonb,
+    last_delivery_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_endpoints_user_enabled ON webhook_endpoints(user_id) WHERE enabled;
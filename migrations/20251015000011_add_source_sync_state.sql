@@ -0,0 +1,11 @@
+-- Incremental sync state of cloud drive sources (Dropbox, OneDrive/SharePoint):
+-- the provider's delta cursor, so a sync only fetches changes since the last one,
+-- and the current OAuth tokens, since providers may rotate the refresh token
+CREATE TABLE IF NOT EXISTS source_sync_state (
+    source_id UUID PRIMARY KEY REFERENCES sources(id) ON DELETE CASCADE,
+    delta_cursor TEXT,
+    access_token TEXT,
+    access_token_expires_at TIMESTAMPTZ,
+    refresh_token TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,36 @@
+-- Per-user data encryption keys (DEKs). Each DEK is stored wrapped by a key
+-- encryption key: the server master key, or a customer-managed KMS key named by
+-- kms_key_ref. Rotation adds a version; retired versions still decrypt old data.
+CREATE TABLE IF NOT EXISTS encryption_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'retired')),
+    -- 'local' (server master key) or 'vault' (HashiCorp Vault transit)
+    provider TEXT NOT NULL,
+    kms_key_ref TEXT,
+    wrapped_key BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    retired_at TIMESTAMPTZ,
+    UNIQUE (user_id, version)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_encryption_keys_one_active
+    ON encryption_keys(user_id) WHERE status = 'active';
+
+-- Audit of key lifecycle and usage. Unwrapping happens when a key is first
+-- needed by a server process, so 'unwrapped' events show which processes read
+-- a user's data and when.
+CREATE TABLE IF NOT EXISTS encryption_key_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    key_id UUID REFERENCES encryption_keys(id) ON DELETE SET NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- created, rotated, rewrapped, unwrapped, unwrap_failed, reencrypted
+    action TEXT NOT NULL,
+    actor_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    detail TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_encryption_key_events_user_created
+    ON encryption_key_events(user_id, created_at DESC);
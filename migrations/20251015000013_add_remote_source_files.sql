@@ -0,0 +1,10 @@
+-- Files an SFTP/FTP source has ingested and left on the server, with the size and
+-- modification time they had, so later polls only download files that changed
+CREATE TABLE IF NOT EXISTS remote_source_files (
+    source_id UUID NOT NULL REFERENCES sources(id) ON DELETE CASCADE,
+    remote_path TEXT NOT NULL,
+    file_size BIGINT NOT NULL,
+    modified_at TIMESTAMPTZ,
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (source_id, remote_path)
+);
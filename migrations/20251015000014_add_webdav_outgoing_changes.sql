@@ -0,0 +1,22 @@
+-- Deletes and renames made in Readur to documents of a bidirectional WebDAV source,
+-- applied to the server at the start of the source's next sync
+CREATE TABLE IF NOT EXISTS webdav_outgoing_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_id UUID NOT NULL REFERENCES sources(id) ON DELETE CASCADE,
+    -- Not a foreign key: deleted documents are gone by the time the change is applied
+    document_id UUID NOT NULL,
+    operation TEXT NOT NULL CHECK (operation IN ('delete', 'rename')),
+    remote_path TEXT NOT NULL,
+    new_remote_path TEXT,
+    expected_etag TEXT,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'applied', 'conflict', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (operation <> 'rename' OR new_remote_path IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webdav_outgoing_changes_pending
+    ON webdav_outgoing_changes(source_id, created_at)
+    WHERE status = 'pending';
@@ -0,0 +1,25 @@
+-- Where the paper originals of documents are kept: shelves holding boxes holding binders
+CREATE TABLE IF NOT EXISTS physical_locations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- A location with sublocations cannot be deleted
+    parent_id UUID REFERENCES physical_locations(id) ON DELETE RESTRICT,
+    kind TEXT NOT NULL CHECK (kind IN ('shelf', 'box', 'binder')),
+    name TEXT NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_physical_locations_unique_name
+    ON physical_locations(user_id, COALESCE(parent_id, '00000000-0000-0000-0000-000000000000'::uuid), LOWER(name));
+CREATE INDEX IF NOT EXISTS idx_physical_locations_parent ON physical_locations(parent_id);
+
+-- A document's original is in at most one location
+CREATE TABLE IF NOT EXISTS document_physical_locations (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    location_id UUID NOT NULL REFERENCES physical_locations(id) ON DELETE CASCADE,
+    assigned_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_physical_locations_location ON document_physical_locations(location_id);
@@ -0,0 +1,22 @@
+-- Progress of indexing documents into an external search engine (Meilisearch or Typesense)
+-- The indexer follows the event log; its position is the last event it applied
+
+CREATE TABLE IF NOT EXISTS external_search_state (
+    engine TEXT NOT NULL,
+    index_name TEXT NOT NULL,
+    last_event_at TIMESTAMPTZ,
+    last_event_id UUID,
+    resync_status TEXT NOT NULL DEFAULT 'never'
+        CHECK (resync_status IN ('never', 'running', 'completed', 'failed')),
+    resync_started_at TIMESTAMPTZ,
+    resync_completed_at TIMESTAMPTZ,
+    resync_documents BIGINT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (engine, index_name)
+);
+
+-- Reading the event log in order from a position
+CREATE INDEX IF NOT EXISTS idx_events_occurred_at_id ON events(occurred_at, id);
+
+COMMENT ON TABLE external_search_state IS 'Event log position and full re-sync status of each external search index';
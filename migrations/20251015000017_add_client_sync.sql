@@ -0,0 +1,50 @@
+-- Differential sync for offline clients: every document change bumps updated_at,
+-- and deletions leave a tombstone clients can pick up after reconnecting
+
+CREATE TRIGGER update_documents_updated_at BEFORE UPDATE ON documents
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Label assignments live outside the documents row but are part of what clients mirror
+CREATE OR REPLACE FUNCTION touch_document_on_label_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        UPDATE documents SET updated_at = NOW() WHERE id = OLD.document_id;
+        RETURN OLD;
+    END IF;
+    UPDATE documents SET updated_at = NOW() WHERE id = NEW.document_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_touch_document_on_label_change
+    AFTER INSERT OR DELETE ON document_labels
+    FOR EACH ROW
+    EXECUTE FUNCTION touch_document_on_label_change();
+
+CREATE TABLE IF NOT EXISTS document_tombstones (
+    document_id UUID PRIMARY KEY,
+    -- No foreign key: deleting a user deletes their documents, which records tombstones
+    user_id UUID NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_tombstones_user_deleted
+    ON document_tombstones(user_id, deleted_at, document_id);
+
+CREATE OR REPLACE FUNCTION record_document_tombstone()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO document_tombstones (document_id, user_id)
+    VALUES (OLD.id, OLD.user_id)
+    ON CONFLICT (document_id) DO UPDATE SET deleted_at = NOW();
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_record_document_tombstone
+    AFTER DELETE ON documents
+    FOR EACH ROW
+    EXECUTE FUNCTION record_document_tombstone();
+
+CREATE INDEX IF NOT EXISTS idx_documents_user_updated_id ON documents(user_id, updated_at, id);
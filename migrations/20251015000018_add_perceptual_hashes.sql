@@ -0,0 +1,22 @@
+-- Perceptual hashes of rendered pages, to find re-scans of the same paper document
+CREATE TABLE IF NOT EXISTS document_page_hashes (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_number INTEGER NOT NULL,
+    -- 64-bit difference hash of the page's content area
+    hash BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (document_id, page_number)
+);
+
+-- Pairs of similar documents a user reviewed and decided are not duplicates;
+-- document_a sorts before document_b
+CREATE TABLE IF NOT EXISTS similar_document_dismissals (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    document_a UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    document_b UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (document_a, document_b),
+    CHECK (document_a < document_b)
+);
+
+CREATE INDEX IF NOT EXISTS idx_similar_document_dismissals_user ON similar_document_dismissals(user_id);
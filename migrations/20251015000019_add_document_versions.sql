@@ -0,0 +1,22 @@
+-- Earlier contents of a document, kept when a file at the same source path changes
+-- or an earlier version is restored. The current contents stay on the document row.
+CREATE TABLE IF NOT EXISTS document_versions (
+    -- Also the storage id the version's file is saved under
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    version_number INTEGER NOT NULL,
+    filename TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    file_size BIGINT NOT NULL,
+    file_hash VARCHAR(64),
+    mime_type TEXT NOT NULL,
+    ocr_text TEXT,
+    ocr_confidence REAL,
+    ocr_word_count INTEGER,
+    original_modified_at TIMESTAMPTZ,
+    -- When these contents were stored, and when newer contents replaced them
+    created_at TIMESTAMPTZ NOT NULL,
+    superseded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (document_id, version_number)
+);
+
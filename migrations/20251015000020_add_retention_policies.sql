@@ -0,0 +1,51 @@
+-- Rules that archive or delete documents once they are older than a retention period.
+-- A policy applies to documents carrying a label, of a document type, or both.
+CREATE TABLE IF NOT EXISTS retention_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    label_id UUID REFERENCES labels(id) ON DELETE CASCADE,
+    -- A MIME type, or a prefix ending in '/' such as 'image/'
+    mime_type TEXT,
+    action TEXT NOT NULL CHECK (action IN ('archive', 'delete')),
+    retain_days INTEGER NOT NULL CHECK (retain_days > 0),
+    -- Days a due deletion waits in the review queue before it is carried out
+    grace_days INTEGER NOT NULL DEFAULT 30 CHECK (grace_days >= 0),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (label_id IS NOT NULL OR mime_type IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_retention_policies_user ON retention_policies(user_id);
+
+-- Deletions waiting out their grace period, which the owner can exempt or approve early
+CREATE TABLE IF NOT EXISTS retention_reviews (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    policy_id UUID NOT NULL REFERENCES retention_policies(id) ON DELETE CASCADE,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'exempted')),
+    due_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    decided_at TIMESTAMPTZ,
+    UNIQUE (policy_id, document_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_retention_reviews_user_status ON retention_reviews(user_id, status, due_at);
+
+-- Every automatic archival and deletion. Rows outlive the policy and the document.
+CREATE TABLE IF NOT EXISTS retention_actions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    policy_id UUID REFERENCES retention_policies(id) ON DELETE SET NULL,
+    policy_name TEXT NOT NULL,
+    document_id UUID NOT NULL,
+    filename TEXT NOT NULL,
+    file_hash VARCHAR(64),
+    action TEXT NOT NULL,
+    performed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_retention_actions_user ON retention_actions(user_id, performed_at DESC);
+CREATE INDEX IF NOT EXISTS idx_retention_actions_policy_document ON retention_actions(policy_id, document_id);
@@ -0,0 +1,23 @@
+-- Significant actions for compliance: uploads, views, downloads, deletions, label and
+-- settings changes and logins, with who did them, from where, and what changed
+CREATE TABLE IF NOT EXISTS audit_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Null for actions taken by the system, such as retention policies, and failed logins
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- Kept so entries stay readable after the user is deleted
+    username TEXT,
+    action TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    resource_id UUID,
+    ip_address TEXT,
+    user_agent TEXT,
+    before JSONB,
+    after JSONB,
+    details JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_user ON audit_log(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log(action, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_resource ON audit_log(resource_type, resource_id);
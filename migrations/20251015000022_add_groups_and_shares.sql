@@ -0,0 +1,80 @@
+-- What a share lets its grantee do; each level includes the ones before it
+DO $$ BEGIN
+    CREATE TYPE share_permission AS ENUM ('view', 'edit', 'delete');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- Teams of users that documents and labels can be shared with
+CREATE TABLE IF NOT EXISTS user_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS user_group_members (
+    group_id UUID NOT NULL REFERENCES user_groups(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (group_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_group_members_user ON user_group_members(user_id);
+
+-- A document shared with a user or a group
+CREATE TABLE IF NOT EXISTS document_shares (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    group_id UUID REFERENCES user_groups(id) ON DELETE CASCADE,
+    permission share_permission NOT NULL DEFAULT 'view',
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((user_id IS NULL) <> (group_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_document_shares_user ON document_shares(document_id, user_id) WHERE user_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_document_shares_group ON document_shares(document_id, group_id) WHERE group_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_document_shares_grantee_user ON document_shares(user_id) WHERE user_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_document_shares_grantee_group ON document_shares(group_id) WHERE group_id IS NOT NULL;
+
+-- A label shared with a user or a group, covering every document of the label's owner
+-- that carries it
+CREATE TABLE IF NOT EXISTS label_shares (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    label_id UUID NOT NULL REFERENCES labels(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    group_id UUID REFERENCES user_groups(id) ON DELETE CASCADE,
+    permission share_permission NOT NULL DEFAULT 'view',
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((user_id IS NULL) <> (group_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_label_shares_user ON label_shares(label_id, user_id) WHERE user_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_label_shares_group ON label_shares(label_id, group_id) WHERE group_id IS NOT NULL;
+
+-- Every (document, user, permission) granted through shares, directly or by group
+-- membership. A user may appear several times for one document; take the highest.
+CREATE OR REPLACE VIEW shared_document_access AS
+SELECT s.document_id, s.user_id, s.permission
+FROM document_shares s
+WHERE s.user_id IS NOT NULL
+UNION ALL
+SELECT s.document_id, m.user_id, s.permission
+FROM document_shares s
+JOIN user_group_members m ON m.group_id = s.group_id
+UNION ALL
+SELECT dl.document_id, grantee.user_id, s.permission
+FROM label_shares s
+JOIN labels l ON l.id = s.label_id
+JOIN document_labels dl ON dl.label_id = s.label_id
+JOIN documents d ON d.id = dl.document_id AND d.user_id = l.user_id
+JOIN LATERAL (
+    SELECT s.user_id WHERE s.user_id IS NOT NULL
+    UNION ALL
+    SELECT m.user_id FROM user_group_members m WHERE m.group_id = s.group_id
+) grantee ON TRUE;
@@ -0,0 +1,23 @@
+-- Public links to a document that work without an account. Only the SHA-256 of the
+-- token is stored; the token itself is shown once when the link is created.
+CREATE TABLE IF NOT EXISTS document_share_links (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    -- bcrypt hash of the optional password
+    password_hash TEXT,
+    expires_at TIMESTAMPTZ,
+    max_downloads INTEGER CHECK (max_downloads IS NULL OR max_downloads > 0),
+    download_count INTEGER NOT NULL DEFAULT 0,
+    -- Stamp every page of PDF downloads with who the link was shared with
+    watermark BOOLEAN NOT NULL DEFAULT FALSE,
+    watermark_text TEXT,
+    revoked_at TIMESTAMPTZ,
+    revoked_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    last_accessed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_share_links_document ON document_share_links(document_id);
+CREATE INDEX IF NOT EXISTS idx_document_share_links_active ON document_share_links(created_at DESC) WHERE revoked_at IS NULL;
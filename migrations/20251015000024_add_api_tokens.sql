@@ -0,0 +1,22 @@
+-- What an API token may do: only read, only upload documents, or everything the user can
+DO $$ BEGIN
+    CREATE TYPE api_token_scope AS ENUM ('read_only', 'upload_only', 'full');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- Long-lived personal access tokens for scanners, scripts and shortcuts. Only the
+-- SHA-256 of the token is stored; the prefix is kept to tell tokens apart.
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    token_prefix TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    scope api_token_scope NOT NULL,
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_tokens_user ON api_tokens(user_id);
@@ -0,0 +1,11 @@
+-- Token buckets for RATE_LIMIT_BACKEND=postgres, shared by every Readur instance
+-- using the database. Keys name the limit and client, e.g. "auth-ip:203.0.113.7".
+CREATE UNLOGGED TABLE IF NOT EXISTS rate_limit_buckets (
+    key TEXT PRIMARY KEY,
+    tokens DOUBLE PRECISION NOT NULL,
+    -- Whether the last request taking a token got one
+    allowed BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_rate_limit_buckets_updated ON rate_limit_buckets(updated_at);
@@ -0,0 +1,16 @@
+-- Saved searches. With notify set, every document that finishes OCR is checked
+-- against the search and its owner gets a notification for each match.
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- Query text, tag and MIME type filters and search mode
+    search JSONB NOT NULL DEFAULT '{}',
+    notify BOOLEAN NOT NULL DEFAULT FALSE,
+    last_matched_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_saved_searches_notify ON saved_searches(user_id) WHERE notify;
@@ -0,0 +1,11 @@
+-- Fuzzy search compares the query with the closest matching words of a document
+-- (pg_trgm word_similarity). A single typo already costs about half the trigrams
+-- of a short word, so 0.8 would only find near-exact matches.
+ALTER TABLE settings ALTER COLUMN fuzzy_search_threshold SET DEFAULT 0.5;
+
+-- Only settings that still hold the old default move to the new one: saving settings
+-- sets updated_at, so rows changed since they were created keep the threshold chosen
+UPDATE settings
+SET fuzzy_search_threshold = 0.5
+WHERE (fuzzy_search_threshold IS NULL OR fuzzy_search_threshold = 0.8::REAL)
+  AND updated_at IS NOT DISTINCT FROM created_at;
@@ -0,0 +1,29 @@
+-- Senders and recipients of documents. Each correspondent can carry a rule that
+-- assigns it to newly processed documents whose text matches.
+CREATE TABLE IF NOT EXISTS correspondents (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- none: never assigned automatically, auto: the name occurs in the text,
+    -- any/all: any or all words of the pattern occur, exact: the pattern occurs
+    -- as a phrase, regex: the pattern is a regular expression that matches
+    match_algorithm TEXT NOT NULL DEFAULT 'auto'
+        CHECK (match_algorithm IN ('none', 'auto', 'any', 'all', 'exact', 'regex')),
+    match_pattern TEXT,
+    case_sensitive BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_correspondents_unique_name ON correspondents(user_id, LOWER(name));
+
+-- A document has at most one correspondent
+CREATE TABLE IF NOT EXISTS document_correspondents (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    correspondent_id UUID NOT NULL REFERENCES correspondents(id) ON DELETE CASCADE,
+    -- manual, rule (a match pattern), detected (the name occurs in the text) or llm
+    assigned_by TEXT NOT NULL CHECK (assigned_by IN ('manual', 'rule', 'detected', 'llm')),
+    assigned_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_correspondents_correspondent ON document_correspondents(correspondent_id);
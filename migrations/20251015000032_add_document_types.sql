@@ -0,0 +1,26 @@
+-- Kinds of document such as Invoice, Contract or Receipt. `fields` lists the custom
+-- fields documents of the type carry, e.g.
+-- [{"name": "total", "type": "amount", "required": true}]
+CREATE TABLE IF NOT EXISTS document_types (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    fields JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_document_types_unique_name ON document_types(user_id, LOWER(name));
+
+-- A document's type and the values of its custom fields, keyed by field name.
+-- Dates are stored as YYYY-MM-DD strings, amounts as numbers and currencies as
+-- ISO 4217 codes.
+CREATE TABLE IF NOT EXISTS document_custom_fields (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    document_type_id UUID NOT NULL REFERENCES document_types(id) ON DELETE CASCADE,
+    fields JSONB NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_custom_fields_type ON document_custom_fields(document_type_id);
+CREATE INDEX IF NOT EXISTS idx_document_custom_fields_fields ON document_custom_fields USING GIN (fields);
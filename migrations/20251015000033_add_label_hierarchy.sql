@@ -0,0 +1,9 @@
+-- Labels can be nested, as in Finance > Taxes > 2024. Searching for a label also
+-- finds documents with any of its descendants. Names stay unique per user, so a
+-- label is still found by its name alone.
+ALTER TABLE labels ADD COLUMN IF NOT EXISTS parent_id UUID REFERENCES labels(id) ON DELETE SET NULL;
+
+ALTER TABLE labels DROP CONSTRAINT IF EXISTS labels_parent_not_self;
+ALTER TABLE labels ADD CONSTRAINT labels_parent_not_self CHECK (parent_id IS NULL OR parent_id <> id);
+
+CREATE INDEX IF NOT EXISTS idx_labels_parent_id ON labels(parent_id) WHERE parent_id IS NOT NULL;
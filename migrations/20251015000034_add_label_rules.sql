@@ -0,0 +1,20 @@
+-- Rules that apply a label automatically. Every condition that is set must hold:
+-- keywords (any or all, as whole words) and the regular expression are checked
+-- against the text after OCR, sources and MIME types already at ingestion.
+CREATE TABLE IF NOT EXISTS label_rules (
+    label_id UUID PRIMARY KEY REFERENCES labels(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    keywords TEXT[] NOT NULL DEFAULT '{}',
+    match_all_keywords BOOLEAN NOT NULL DEFAULT FALSE,
+    regex TEXT,
+    -- Source names or types, or how the document arrived such as web_upload
+    sources TEXT[] NOT NULL DEFAULT '{}',
+    -- MIME types, image/* style wildcards allowed
+    mime_types TEXT[] NOT NULL DEFAULT '{}',
+    case_sensitive BOOLEAN NOT NULL DEFAULT FALSE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_label_rules_user_enabled ON label_rules(user_id) WHERE enabled;
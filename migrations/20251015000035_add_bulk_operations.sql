@@ -0,0 +1,27 @@
+-- Operations on many documents at once, run in the background. The documents are
+-- resolved when the operation is submitted and processed in array order, so
+-- `processed_documents` doubles as the cursor an interrupted operation resumes from.
+CREATE TABLE IF NOT EXISTS bulk_operations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    operation TEXT NOT NULL
+        CHECK (operation IN ('add_labels', 'remove_labels', 'delete', 'reprocess_ocr', 'change_owner', 'merge')),
+    -- Label ids, new owner or merge options, depending on the operation
+    parameters JSONB NOT NULL DEFAULT '{}',
+    document_ids UUID[] NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'completed', 'failed')),
+    total_documents INTEGER NOT NULL,
+    processed_documents INTEGER NOT NULL DEFAULT 0,
+    succeeded_documents INTEGER NOT NULL DEFAULT 0,
+    failed_documents INTEGER NOT NULL DEFAULT 0,
+    -- The first failures, as [{"document_id": ..., "error": ...}]
+    errors JSONB NOT NULL DEFAULT '[]',
+    merged_document_id UUID REFERENCES documents(id) ON DELETE SET NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_bulk_operations_user ON bulk_operations(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_bulk_operations_unfinished ON bulk_operations(status) WHERE status IN ('queued', 'running');
@@ -0,0 +1,29 @@
+-- Signed webhook deliveries with retries. Every endpoint gets a secret its deliveries
+-- are signed with; every delivery of an event to an endpoint is logged, and failed
+-- deliveries are retried with exponential backoff until they succeed or run out of
+-- attempts.
+ALTER TABLE webhook_endpoints ADD COLUMN IF NOT EXISTS secret TEXT;
+UPDATE webhook_endpoints
+SET secret = replace(gen_random_uuid()::text, '-', '') || replace(gen_random_uuid()::text, '-', '')
+WHERE secret IS NULL;
+ALTER TABLE webhook_endpoints ALTER COLUMN secret SET NOT NULL;
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    endpoint_id UUID NOT NULL REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+    event_id UUID NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    event_type TEXT NOT NULL,
+    -- pending: not delivered yet, retried at next_attempt_at
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'succeeded', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ,
+    -- HTTP status of the last attempt; NULL when no response arrived
+    response_status INTEGER,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_attempt_at TIMESTAMPTZ,
+    UNIQUE (endpoint_id, event_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_endpoint ON webhook_deliveries(endpoint_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(next_attempt_at) WHERE status = 'pending';
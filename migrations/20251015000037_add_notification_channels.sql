@@ -0,0 +1,26 @@
+-- Outbound notification channels. Users send their notifications to email, ntfy, Gotify
+-- or Slack in addition to the in-app list, choosing per channel which notification
+-- types it receives.
+CREATE TABLE IF NOT EXISTS notification_channels (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- Channel type and its destination settings, e.g. the ntfy server and topic
+    config JSONB NOT NULL CHECK (config->>'type' IN ('email', 'ntfy', 'gotify', 'slack')),
+    -- Notification types (success, error, info, warning) the channel receives
+    notification_types TEXT[] NOT NULL DEFAULT ARRAY['success', 'error', 'info', 'warning'],
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_sent_at TIMESTAMPTZ,
+    -- Error of the most recent send, cleared when a send succeeds
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_channels_user ON notification_channels(user_id);
+
+-- Notifications not yet sent to the outbound channels of their user. Existing
+-- notifications are treated as sent so enabling a channel does not replay history.
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS dispatched_at TIMESTAMPTZ;
+UPDATE notifications SET dispatched_at = created_at WHERE dispatched_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_notifications_undispatched ON notifications(created_at) WHERE dispatched_at IS NULL;
@@ -0,0 +1,25 @@
+-- Library exports: ZIP archives of original files and a manifest with metadata, labels,
+-- OCR text and knowledge graphs, built in the background. The documents are resolved
+-- when the export is requested; the archive is written to the exports directory under
+-- the upload path and named after the export.
+CREATE TABLE IF NOT EXISTS exports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Every user's library instead of the requesting user's; admins only
+    all_users BOOLEAN NOT NULL DEFAULT FALSE,
+    document_ids UUID[] NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'completed', 'failed')),
+    total_documents INTEGER NOT NULL,
+    processed_documents INTEGER NOT NULL DEFAULT 0,
+    failed_documents INTEGER NOT NULL DEFAULT 0,
+    -- The first documents whose file could not be read, as [{"document_id": ..., "error": ...}]
+    errors JSONB NOT NULL DEFAULT '[]',
+    archive_size BIGINT,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_exports_user ON exports(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_exports_unfinished ON exports(status) WHERE status IN ('queued', 'running');
@@ -0,0 +1,32 @@
+-- Restores of export archives, run in the background. The uploaded archive is kept in
+-- the imports directory under the upload path, named after the import, until the
+-- import finishes. Documents whose owner already has a file with the same hash are
+-- skipped, so an interrupted import can start over without creating duplicates.
+CREATE TABLE IF NOT EXISTS imports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Create the archive's users that do not exist instead of giving every document
+    -- to the importing user; admins only
+    recreate_users BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Create the archive's labels that do not exist instead of only assigning
+    -- existing labels of the same name
+    recreate_labels BOOLEAN NOT NULL DEFAULT FALSE,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'completed', 'failed')),
+    -- Known once the manifest is read
+    total_documents INTEGER NOT NULL DEFAULT 0,
+    processed_documents INTEGER NOT NULL DEFAULT 0,
+    imported_documents INTEGER NOT NULL DEFAULT 0,
+    skipped_documents INTEGER NOT NULL DEFAULT 0,
+    failed_documents INTEGER NOT NULL DEFAULT 0,
+    created_users INTEGER NOT NULL DEFAULT 0,
+    created_labels INTEGER NOT NULL DEFAULT 0,
+    -- The first failures, as [{"document_id": ..., "error": ...}] with the IDs of the manifest
+    errors JSONB NOT NULL DEFAULT '[]',
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_imports_user ON imports(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_imports_unfinished ON imports(status) WHERE status IN ('queued', 'running');
@@ -0,0 +1,30 @@
+-- Scheduled backups: library exports of every user's documents uploaded to the S3 bucket
+-- or WebDAV collection configured with BACKUP_DESTINATION. An incremental backup only
+-- holds the documents created or changed since the backup before it. Backups removed
+-- from the destination by the retention count are kept here as 'pruned'.
+CREATE TABLE IF NOT EXISTS backups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- The export the archive was written by; deleted once it is uploaded
+    export_id UUID REFERENCES exports(id) ON DELETE SET NULL,
+    destination TEXT NOT NULL CHECK (destination IN ('s3', 'webdav')),
+    kind TEXT NOT NULL CHECK (kind IN ('full', 'incremental')),
+    -- Start of the backup an incremental backup continues from
+    changed_since TIMESTAMPTZ,
+    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'failed', 'pruned')),
+    -- The admin who started it, or NULL when it was scheduled
+    triggered_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- S3 key or WebDAV URL of the uploaded archive
+    remote_path TEXT,
+    archive_size BIGINT,
+    total_documents INTEGER NOT NULL DEFAULT 0,
+    -- Documents backed up without their file
+    failed_documents INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    pruned_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_backups_created ON backups(created_at DESC);
+-- Only one backup runs at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_backups_one_running ON backups((TRUE)) WHERE status = 'running';
@@ -0,0 +1,5 @@
+-- Storage migrations can move document files into Azure Blob Storage
+
+ALTER TABLE storage_migrations DROP CONSTRAINT IF EXISTS storage_migrations_target_backend_check;
+ALTER TABLE storage_migrations ADD CONSTRAINT storage_migrations_target_backend_check
+    CHECK (target_backend IN ('local', 's3', 'azure'));
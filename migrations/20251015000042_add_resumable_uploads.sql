@@ -0,0 +1,23 @@
+-- Resumable uploads (tus protocol). Received bytes are appended to a part file in the
+-- temp directory under the upload path, named after the upload, until the upload is
+-- complete and ingested. Uploads that see no progress before they expire are removed
+-- together with their part file.
+CREATE TABLE IF NOT EXISTS resumable_uploads (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    upload_length BIGINT NOT NULL CHECK (upload_length > 0),
+    upload_offset BIGINT NOT NULL DEFAULT 0 CHECK (upload_offset >= 0 AND upload_offset <= upload_length),
+    ocr_language TEXT,
+    status TEXT NOT NULL DEFAULT 'uploading' CHECK (status IN ('uploading', 'completed', 'failed')),
+    -- The ingested document, or the existing document the file duplicates
+    document_id UUID REFERENCES documents(id) ON DELETE SET NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_resumable_uploads_user ON resumable_uploads(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_resumable_uploads_expires ON resumable_uploads(expires_at);
@@ -0,0 +1,13 @@
+-- Content-addressed document files. With STORAGE_DEDUPLICATION on, identical files
+-- are stored once under blobs/ and documents point at the shared blob. ref_count is
+-- the number of documents referencing the blob; the blob file is deleted together
+-- with its row when the count reaches zero. A blob stored again after that gets a
+-- new path, so a concurrent delete never removes a file that is in use.
+CREATE TABLE IF NOT EXISTS file_blobs (
+    hash TEXT PRIMARY KEY,
+    storage_path TEXT NOT NULL UNIQUE,
+    size BIGINT NOT NULL CHECK (size >= 0),
+    ref_count INTEGER NOT NULL DEFAULT 1 CHECK (ref_count >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
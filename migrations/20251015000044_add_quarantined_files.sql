@@ -0,0 +1,21 @@
+-- Incoming files the malware scanner (CLAMAV_ADDRESS) found infected. They never become
+-- documents: the file is kept in the quarantine directory of the upload path, out of
+-- reach of its owner, until an admin deletes it.
+CREATE TABLE IF NOT EXISTS quarantined_files (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    original_filename TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    file_size BIGINT NOT NULL,
+    file_hash TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    -- What the scanner reported, e.g. 'Win.Test.EICAR_HDB-1'
+    signature TEXT NOT NULL,
+    source_type TEXT,
+    source_id UUID,
+    source_path TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_quarantined_files_created ON quarantined_files(created_at DESC);
@@ -0,0 +1,33 @@
+-- Where geotagged documents were captured, from the GPS position in their EXIF data
+-- (source_metadata gps_latitude/gps_longitude). Searches filter on it with near= and bbox=.
+CREATE TABLE IF NOT EXISTS document_coordinates (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    latitude DOUBLE PRECISION NOT NULL CHECK (latitude BETWEEN -90 AND 90),
+    longitude DOUBLE PRECISION NOT NULL CHECK (longitude BETWEEN -180 AND 180),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_coordinates_lat_lon ON document_coordinates(latitude, longitude);
+
+-- Great-circle distance in kilometers, by the haversine formula
+CREATE OR REPLACE FUNCTION readur_distance_km(
+    lat1 DOUBLE PRECISION,
+    lon1 DOUBLE PRECISION,
+    lat2 DOUBLE PRECISION,
+    lon2 DOUBLE PRECISION
+) RETURNS DOUBLE PRECISION AS $$
+    SELECT 2 * 6371.0088 * asin(sqrt(least(1.0,
+        power(sin(radians(lat2 - lat1) / 2), 2)
+        + cos(radians(lat1)) * cos(radians(lat2)) * power(sin(radians(lon2 - lon1) / 2), 2)
+    )))
+$$ LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE;
+
+-- Documents uploaded before coordinates were kept
+INSERT INTO document_coordinates (document_id, latitude, longitude)
+SELECT id, (source_metadata->>'gps_latitude')::double precision, (source_metadata->>'gps_longitude')::double precision
+FROM documents
+WHERE jsonb_typeof(source_metadata->'gps_latitude') = 'number'
+  AND jsonb_typeof(source_metadata->'gps_longitude') = 'number'
+  AND (source_metadata->>'gps_latitude')::double precision BETWEEN -90 AND 90
+  AND (source_metadata->>'gps_longitude')::double precision BETWEEN -180 AND 180
+ON CONFLICT (document_id) DO NOTHING;
@@ -0,0 +1,17 @@
+-- When a source syncs on its own. Without a cron expression it syncs every
+-- sync_interval_minutes of its config, as before; quiet hours defer scheduled syncs
+-- and max_runtime_minutes stops syncs that run longer. Also keeps how long the last
+-- sync took, for every source that has synced.
+CREATE TABLE IF NOT EXISTS source_schedules (
+    source_id UUID PRIMARY KEY REFERENCES sources(id) ON DELETE CASCADE,
+    -- Five fields, evaluated in UTC, e.g. '0 2 * * *'
+    cron_expression TEXT,
+    -- Times of day in UTC; the window wraps past midnight when it ends before it starts
+    quiet_hours_start TIME,
+    quiet_hours_end TIME,
+    max_runtime_minutes INTEGER CHECK (max_runtime_minutes > 0),
+    last_sync_started_at TIMESTAMPTZ,
+    last_sync_duration_ms BIGINT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((quiet_hours_start IS NULL) = (quiet_hours_end IS NULL))
+);
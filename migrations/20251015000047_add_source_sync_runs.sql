@@ -0,0 +1,21 @@
+-- One row per sync of a source: how it ended, what it did to the files it saw and
+-- why files failed, so a file missing after a sync can be explained without the
+-- server logs. Only the latest runs of each source are kept.
+CREATE TABLE IF NOT EXISTS source_sync_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_id UUID NOT NULL REFERENCES sources(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'running'
+        CHECK (status IN ('running', 'succeeded', 'failed', 'cancelled', 'interrupted')),
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ,
+    files_added INTEGER NOT NULL DEFAULT 0,
+    files_updated INTEGER NOT NULL DEFAULT 0,
+    files_skipped INTEGER NOT NULL DEFAULT 0,
+    files_failed INTEGER NOT NULL DEFAULT 0,
+    -- Why the whole run failed
+    error TEXT,
+    -- [{"path": ..., "error": ...}] for the first failed files of the run
+    file_errors JSONB NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX IF NOT EXISTS idx_source_sync_runs_source_started ON source_sync_runs(source_id, started_at DESC);
@@ -0,0 +1,53 @@
+-- What a member may do in a workspace; each level includes the ones before it
+DO $$ BEGIN
+    CREATE TYPE workspace_role AS ENUM ('viewer', 'editor', 'admin');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- Isolated libraries for multi-tenant instances (MULTI_TENANT_MODE). Documents,
+-- labels and sources created in a workspace are visible to its members only.
+CREATE TABLE IF NOT EXISTS workspaces (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    -- Overrides of user settings for work done in the workspace
+    settings JSONB NOT NULL DEFAULT '{}',
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS workspace_members (
+    workspace_id UUID NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role workspace_role NOT NULL DEFAULT 'editor',
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (workspace_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_workspace_members_user ON workspace_members(user_id);
+
+-- NULL keeps a row personal to its owner, as before workspaces existed
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces(id) ON DELETE CASCADE;
+ALTER TABLE labels ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces(id) ON DELETE CASCADE;
+ALTER TABLE sources ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_documents_workspace ON documents(workspace_id) WHERE workspace_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_labels_workspace ON labels(workspace_id) WHERE workspace_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_sources_workspace ON sources(workspace_id) WHERE workspace_id IS NOT NULL;
+
+-- Documents imported by a source land in the source's workspace
+CREATE OR REPLACE FUNCTION inherit_source_workspace() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.workspace_id IS NULL AND NEW.source_id IS NOT NULL THEN
+        SELECT workspace_id INTO NEW.workspace_id FROM sources WHERE id = NEW.source_id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS documents_inherit_source_workspace ON documents;
+CREATE TRIGGER documents_inherit_source_workspace
+    BEFORE INSERT ON documents
+    FOR EACH ROW EXECUTE FUNCTION inherit_source_workspace();
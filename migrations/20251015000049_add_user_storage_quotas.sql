@@ -0,0 +1,11 @@
+-- Storage quotas set by admins for individual users, overriding the default from
+-- DEFAULT_STORAGE_QUOTA_MB. A NULL quota exempts the user from any limit.
+CREATE TABLE IF NOT EXISTS user_storage_quotas (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    quota_bytes BIGINT CHECK (quota_bytes IS NULL OR quota_bytes >= 0),
+    set_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Usage is the sum of the sizes of a user's documents
+CREATE INDEX IF NOT EXISTS idx_documents_user_file_size ON documents(user_id) INCLUDE (file_size);
@@ -0,0 +1,40 @@
+-- Highlights, rectangles and comments that users anchor to a spot on a document page,
+-- with threaded replies, so a document can be discussed where it says something.
+-- Positions are fractions of the page size so they survive different render sizes.
+DO $$ BEGIN
+    CREATE TYPE annotation_kind AS ENUM ('highlight', 'rectangle', 'comment');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE TABLE IF NOT EXISTS document_annotations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    kind annotation_kind NOT NULL,
+    page INTEGER NOT NULL CHECK (page >= 1),
+    x DOUBLE PRECISION NOT NULL CHECK (x >= 0 AND x <= 1),
+    y DOUBLE PRECISION NOT NULL CHECK (y >= 0 AND y <= 1),
+    width DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (width >= 0 AND x + width <= 1),
+    height DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (height >= 0 AND y + height <= 1),
+    color TEXT,
+    -- The highlighted text or the note of a rectangle or comment
+    text TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_annotations_document ON document_annotations(document_id, page);
+
+CREATE TABLE IF NOT EXISTS annotation_comments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    annotation_id UUID NOT NULL REFERENCES document_annotations(id) ON DELETE CASCADE,
+    -- The comment this one replies to; top-level comments have none
+    parent_id UUID REFERENCES annotation_comments(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_annotation_comments_annotation ON annotation_comments(annotation_id, created_at);
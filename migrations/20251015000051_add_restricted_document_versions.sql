@@ -0,0 +1,4 @@
+-- Redacting a document keeps its unredacted contents as earlier versions. Those are
+-- marked restricted so only the document's owner and admins can list, compare or
+-- restore them.
+ALTER TABLE document_versions ADD COLUMN IF NOT EXISTS restricted BOOLEAN NOT NULL DEFAULT FALSE;
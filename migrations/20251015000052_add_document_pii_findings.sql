@@ -0,0 +1,29 @@
+-- Personal data (social security numbers, IBANs, card numbers, email addresses and
+-- phone numbers) found in the OCR text of documents, so it can be reviewed and redacted.
+-- Offsets are byte offsets into the text the scan ran over.
+DO $$ BEGIN
+    CREATE TYPE pii_kind AS ENUM ('ssn', 'iban', 'credit_card', 'email', 'phone');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE TABLE IF NOT EXISTS document_pii_findings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    kind pii_kind NOT NULL,
+    text TEXT NOT NULL,
+    start_offset INTEGER NOT NULL CHECK (start_offset >= 0),
+    end_offset INTEGER NOT NULL CHECK (end_offset > start_offset),
+    page_number INTEGER,
+    detected_by TEXT NOT NULL CHECK (detected_by IN ('regex', 'llm')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_pii_findings_document ON document_pii_findings(document_id, start_offset);
+
+-- When a document was last scanned, so "nothing found" can be told from "not scanned"
+CREATE TABLE IF NOT EXISTS document_pii_scans (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    scanned_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    llm_used BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,14 @@
+-- Per-user iCalendar feeds of document dates such as invoice due dates and contract
+-- renewals. Calendar apps cannot log in, so the feed URL carries a secret token; only
+-- its hash is stored.
+CREATE TABLE IF NOT EXISTS calendar_feeds (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    -- Days before each date that calendars remind of it
+    lead_days INTEGER[] NOT NULL DEFAULT '{7,1}',
+    -- Date custom fields that become events
+    date_fields TEXT[] NOT NULL DEFAULT '{due_date,renewal_date,expiration_date}',
+    last_accessed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,26 @@
+-- Reminders users set on documents ("review this contract on 2025-03-01"). A scheduler
+-- turns due reminders into notifications, which go out on the user's notification
+-- channels; users can snooze or complete them.
+DO $$ BEGIN
+    CREATE TYPE reminder_status AS ENUM ('pending', 'sent', 'completed');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE TABLE IF NOT EXISTS document_reminders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    note TEXT,
+    remind_at TIMESTAMPTZ NOT NULL,
+    status reminder_status NOT NULL DEFAULT 'pending',
+    sent_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    snooze_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_reminders_due ON document_reminders(remind_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_document_reminders_user ON document_reminders(user_id, remind_at);
+CREATE INDEX IF NOT EXISTS idx_document_reminders_document ON document_reminders(document_id);
@@ -0,0 +1,19 @@
+-- Review workflow for incoming documents: new documents land in the inbox, are
+-- reviewed, and are then filed or rejected. Users can keep documents out of search
+-- until they have been reviewed.
+DO $$ BEGIN
+    CREATE TYPE document_workflow_state AS ENUM ('inbox', 'reviewed', 'filed', 'rejected');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- Documents that already exist have been dealt with, so they start out filed; new
+-- documents start in the inbox
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS workflow_state document_workflow_state NOT NULL DEFAULT 'filed';
+ALTER TABLE documents ALTER COLUMN workflow_state SET DEFAULT 'inbox';
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS workflow_state_changed_at TIMESTAMPTZ;
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS workflow_state_changed_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_documents_workflow_state ON documents(user_id, workflow_state, created_at DESC);
+
+ALTER TABLE settings ADD COLUMN IF NOT EXISTS require_review_before_search BOOLEAN NOT NULL DEFAULT FALSE;
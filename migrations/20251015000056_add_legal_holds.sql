@@ -0,0 +1,57 @@
+-- Legal holds preserve documents for a matter (litigation, audit, investigation):
+-- while a document is on an active hold it cannot be deleted, renamed, given away
+-- or have its file replaced, whoever asks. The trigger enforces this below the
+-- application as well, for every path that changes documents.
+CREATE TABLE IF NOT EXISTS legal_holds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    reason TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    released_at TIMESTAMPTZ,
+    released_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE TABLE IF NOT EXISTS legal_hold_documents (
+    hold_id UUID NOT NULL REFERENCES legal_holds(id) ON DELETE CASCADE,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    added_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (hold_id, document_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_legal_hold_documents_document ON legal_hold_documents(document_id);
+
+CREATE OR REPLACE FUNCTION enforce_legal_hold()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM legal_hold_documents d
+        JOIN legal_holds h ON h.id = d.hold_id
+        WHERE d.document_id = OLD.id AND h.released_at IS NULL
+    ) THEN
+        RAISE EXCEPTION 'Document % is on legal hold', OLD.id USING ERRCODE = 'RLH01';
+    END IF;
+
+    IF TG_OP = 'DELETE' THEN
+        RETURN OLD;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS documents_legal_hold_delete ON documents;
+CREATE TRIGGER documents_legal_hold_delete
+    BEFORE DELETE ON documents
+    FOR EACH ROW EXECUTE FUNCTION enforce_legal_hold();
+
+-- Storage migrations may move a held file as long as its contents stay the same
+DROP TRIGGER IF EXISTS documents_legal_hold_update ON documents;
+CREATE TRIGGER documents_legal_hold_update
+    BEFORE UPDATE OF file_hash, file_size, original_filename, user_id ON documents
+    FOR EACH ROW
+    WHEN (OLD.file_hash IS DISTINCT FROM NEW.file_hash
+          OR OLD.file_size IS DISTINCT FROM NEW.file_size
+          OR OLD.original_filename IS DISTINCT FROM NEW.original_filename
+          OR OLD.user_id IS DISTINCT FROM NEW.user_id)
+    EXECUTE FUNCTION enforce_legal_hold();
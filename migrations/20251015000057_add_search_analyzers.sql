@@ -0,0 +1,111 @@
+-- Admin-chosen analyzers for the readur_<language> text search configurations,
+-- applied with readur_configure_search_analyzer(). Languages without a row keep
+-- the mapping set up in add_search_folding.
+CREATE TABLE IF NOT EXISTS search_analyzers (
+    -- Postgres language name, as in readur_english, or 'simple'
+    language TEXT PRIMARY KEY,
+    fold_accents BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Dictionaries a word is looked up in, in order, after accent folding
+    dictionaries TEXT[] NOT NULL,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Words a search for a term also finds, applied to queries so they take effect
+-- without reindexing
+CREATE TABLE IF NOT EXISTS search_synonyms (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    language TEXT NOT NULL,
+    term TEXT NOT NULL,
+    synonyms TEXT[] NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (language, term)
+);
+
+CREATE TABLE IF NOT EXISTS search_index_rebuilds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Only documents indexed with this language; NULL for every document
+    language TEXT,
+    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'failed')),
+    total_documents BIGINT NOT NULL DEFAULT 0,
+    processed_documents BIGINT NOT NULL DEFAULT 0,
+    -- Documents are rebuilt in id order; a rebuild interrupted by a shutdown
+    -- continues after this one
+    last_document_id UUID,
+    error TEXT,
+    started_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- One rebuild at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_search_index_rebuilds_running
+    ON search_index_rebuilds ((TRUE)) WHERE status = 'running';
+CREATE INDEX IF NOT EXISTS idx_search_index_rebuilds_started ON search_index_rebuilds(started_at DESC);
+
+-- Point readur_<lang> at the dictionaries, behind unaccent when fold_accents.
+-- ASCII words have no accents to fold and go straight to the dictionaries.
+CREATE OR REPLACE FUNCTION readur_configure_search_analyzer(lang TEXT, fold_accents BOOLEAN, dictionaries TEXT[])
+RETURNS void AS $$
+DECLARE
+    chain TEXT;
+BEGIN
+    SELECT string_agg(quote_ident(d), ', ' ORDER BY n) INTO chain
+    FROM unnest(dictionaries) WITH ORDINALITY AS t(d, n);
+    IF chain IS NULL THEN
+        RAISE EXCEPTION 'At least one dictionary is required';
+    END IF;
+
+    EXECUTE format(
+        'ALTER TEXT SEARCH CONFIGURATION %I ALTER MAPPING FOR asciiword, asciihword, hword_asciipart WITH %s',
+        'readur_' || lang, chain
+    );
+    EXECUTE format(
+        'ALTER TEXT SEARCH CONFIGURATION %I ALTER MAPPING FOR hword, hword_part, word WITH %s',
+        'readur_' || lang, CASE WHEN fold_accents THEN 'unaccent, ' || chain ELSE chain END
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+-- Query for a term and its synonyms, any of which matches
+CREATE OR REPLACE FUNCTION readur_synonym_query(config regconfig, term TEXT, synonyms TEXT[])
+RETURNS tsquery AS $$
+DECLARE
+    synonym TEXT;
+    result tsquery := plainto_tsquery(config, term);
+BEGIN
+    FOREACH synonym IN ARRAY synonyms LOOP
+        result := result || plainto_tsquery(config, synonym);
+    END LOOP;
+    RETURN result;
+END;
+$$ LANGUAGE plpgsql STABLE;
+
+-- As before, with each configuration's part rewritten to also match the synonyms
+-- of its terms
+CREATE OR REPLACE FUNCTION readur_search_query(query_text TEXT, mode TEXT DEFAULT 'plain')
+RETURNS tsquery AS $$
+DECLARE
+    config regconfig;
+    part tsquery;
+    result tsquery;
+BEGIN
+    FOREACH config IN ARRAY readur_search_configs() LOOP
+        part := CASE mode
+            WHEN 'phrase' THEN phraseto_tsquery(config, query_text)
+            WHEN 'boolean' THEN to_tsquery(config, query_text)
+            ELSE plainto_tsquery(config, query_text)
+        END;
+        IF EXISTS (SELECT 1 FROM search_synonyms WHERE 'readur_' || language = config::text) THEN
+            part := ts_rewrite(part, format(
+                'SELECT plainto_tsquery(%L::regconfig, term), readur_synonym_query(%L::regconfig, term, synonyms) '
+                'FROM search_synonyms WHERE ''readur_'' || language = %L',
+                config, config, config::text
+            ));
+        END IF;
+        result := CASE WHEN result IS NULL THEN part ELSE result || part END;
+    END LOOP;
+    RETURN result;
+END;
+$$ LANGUAGE plpgsql STABLE;
@@ -0,0 +1,30 @@
+-- Knowledge-graph extraction over every document matching a filter, run in the
+-- background with a limited number of LLM requests at a time
+CREATE TABLE IF NOT EXISTS analysis_batches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    filter JSONB NOT NULL DEFAULT '{}'::jsonb,
+    -- Documents to analyze, in order; progress is the number already processed
+    document_ids UUID[] NOT NULL DEFAULT '{}',
+    concurrency INTEGER NOT NULL CHECK (concurrency > 0),
+    model TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'completed', 'failed', 'cancelled')),
+    total_documents INTEGER NOT NULL DEFAULT 0,
+    processed_documents INTEGER NOT NULL DEFAULT 0,
+    succeeded_documents INTEGER NOT NULL DEFAULT 0,
+    failed_documents INTEGER NOT NULL DEFAULT 0,
+    errors JSONB NOT NULL DEFAULT '[]'::jsonb,
+    estimated_input_tokens BIGINT NOT NULL DEFAULT 0,
+    estimated_output_tokens BIGINT NOT NULL DEFAULT 0,
+    estimated_cost DOUBLE PRECISION,
+    error TEXT,
+    cancelled_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_analysis_batches_user ON analysis_batches(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_analysis_batches_unfinished ON analysis_batches(created_at)
+    WHERE status IN ('queued', 'running');
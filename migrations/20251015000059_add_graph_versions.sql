@@ -0,0 +1,53 @@
+-- Every analysis of a document keeps its knowledge graph as a new version instead of
+-- replacing the previous one
+CREATE TABLE IF NOT EXISTS document_graph_versions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    version_number INTEGER NOT NULL,
+    -- NULL for graphs restored from an export
+    model TEXT,
+    node_count INTEGER NOT NULL DEFAULT 0,
+    edge_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (document_id, version_number)
+);
+
+ALTER TABLE document_nodes ADD COLUMN IF NOT EXISTS version_id UUID REFERENCES document_graph_versions(id) ON DELETE CASCADE;
+ALTER TABLE document_edges ADD COLUMN IF NOT EXISTS version_id UUID REFERENCES document_graph_versions(id) ON DELETE CASCADE;
+
+-- Graphs extracted so far become version 1
+INSERT INTO document_graph_versions (document_id, version_number, node_count, edge_count, created_at)
+SELECT n.document_id, 1, COUNT(*),
+       (SELECT COUNT(*) FROM document_edges e WHERE e.document_id = n.document_id),
+       COALESCE(MIN(n.created_at), NOW())
+FROM document_nodes n
+GROUP BY n.document_id;
+
+UPDATE document_nodes n SET version_id = v.id
+FROM document_graph_versions v WHERE v.document_id = n.document_id AND n.version_id IS NULL;
+
+UPDATE document_edges e SET version_id = v.id
+FROM document_graph_versions v WHERE v.document_id = e.document_id AND e.version_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_document_nodes_version_id ON document_nodes(version_id);
+CREATE INDEX IF NOT EXISTS idx_document_edges_version_id ON document_edges(version_id);
+
+-- Human corrections, applied on top of whichever version is shown. They name entities
+-- and relationships, not node ids, so they survive re-analysis.
+CREATE TABLE IF NOT EXISTS document_graph_corrections (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('rename_node', 'delete_node', 'delete_edge')),
+    node_label TEXT,
+    node_name TEXT,
+    new_label TEXT,
+    new_name TEXT,
+    source_name TEXT,
+    target_name TEXT,
+    relationship TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_graph_corrections_document
+    ON document_graph_corrections(document_id, created_at);
@@ -0,0 +1,72 @@
+-- Entities found in a user's documents, identified by type and name ignoring case and
+-- surrounding spaces, so every mention of the same entity leads to one page
+CREATE TABLE IF NOT EXISTS graph_entities (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    label TEXT NOT NULL,
+    -- Name as first found
+    name TEXT NOT NULL,
+    normalized_name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, label, normalized_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_graph_entities_user_name ON graph_entities(user_id, normalized_name);
+CREATE INDEX IF NOT EXISTS idx_document_nodes_label_name ON document_nodes(label, lower(btrim(name)));
+
+CREATE OR REPLACE FUNCTION register_graph_entity(owner UUID, entity_label TEXT, entity_name TEXT)
+RETURNS VOID AS $$
+BEGIN
+    IF owner IS NULL OR entity_label IS NULL OR btrim(COALESCE(entity_name, '')) = '' THEN
+        RETURN;
+    END IF;
+    INSERT INTO graph_entities (user_id, label, name, normalized_name)
+    VALUES (owner, entity_label, btrim(entity_name), lower(btrim(entity_name)))
+    ON CONFLICT (user_id, label, normalized_name) DO NOTHING;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Every extracted node and every entity renamed by a correction gets an entity
+CREATE OR REPLACE FUNCTION register_node_entity() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM register_graph_entity(
+        (SELECT user_id FROM documents WHERE id = NEW.document_id), NEW.label, NEW.name);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS document_nodes_register_entity ON document_nodes;
+CREATE TRIGGER document_nodes_register_entity
+    AFTER INSERT ON document_nodes
+    FOR EACH ROW EXECUTE FUNCTION register_node_entity();
+
+CREATE OR REPLACE FUNCTION register_corrected_entity() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.kind = 'rename_node' THEN
+        PERFORM register_graph_entity(
+            (SELECT user_id FROM documents WHERE id = NEW.document_id), NEW.new_label, NEW.new_name);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS document_graph_corrections_register_entity ON document_graph_corrections;
+CREATE TRIGGER document_graph_corrections_register_entity
+    AFTER INSERT ON document_graph_corrections
+    FOR EACH ROW EXECUTE FUNCTION register_corrected_entity();
+
+INSERT INTO graph_entities (user_id, label, name, normalized_name)
+SELECT DISTINCT ON (d.user_id, n.label, lower(btrim(n.name))) d.user_id, n.label, btrim(n.name), lower(btrim(n.name))
+FROM document_nodes n
+JOIN documents d ON d.id = n.document_id
+WHERE btrim(n.name) <> ''
+ORDER BY d.user_id, n.label, lower(btrim(n.name)), n.created_at
+ON CONFLICT (user_id, label, normalized_name) DO NOTHING;
+
+INSERT INTO graph_entities (user_id, label, name, normalized_name)
+SELECT DISTINCT ON (d.user_id, c.new_label, lower(btrim(c.new_name))) d.user_id, c.new_label, btrim(c.new_name), lower(btrim(c.new_name))
+FROM document_graph_corrections c
+JOIN documents d ON d.id = c.document_id
+WHERE c.kind = 'rename_node' AND c.new_label IS NOT NULL AND btrim(COALESCE(c.new_name, '')) <> ''
+ORDER BY d.user_id, c.new_label, lower(btrim(c.new_name)), c.created_at
+ON CONFLICT (user_id, label, normalized_name) DO NOTHING;
@@ -0,0 +1,18 @@
+-- Dated events extracted from a document along with its knowledge graph ("contract
+-- signed 2021-05-03"); they belong to a graph version and go away with it
+CREATE TABLE IF NOT EXISTS document_timeline_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    version_id UUID NOT NULL REFERENCES document_graph_versions(id) ON DELETE CASCADE,
+    -- First day of the month or year when only those are known
+    event_date DATE NOT NULL,
+    date_precision TEXT NOT NULL DEFAULT 'day'
+        CHECK (date_precision IN ('day', 'month', 'year')),
+    description TEXT NOT NULL,
+    -- Names of the entities involved, as extracted
+    entities TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_timeline_events_version ON document_timeline_events(version_id, event_date);
+CREATE INDEX IF NOT EXISTS idx_document_timeline_events_document ON document_timeline_events(document_id, event_date);
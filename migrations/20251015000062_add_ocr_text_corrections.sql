@@ -0,0 +1,20 @@
+-- OCR text corrected by hand: who last edited it and when
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS ocr_text_corrected_at TIMESTAMPTZ;
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS ocr_text_corrected_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+-- Text written by anything else (OCR reruns, region OCR) is no longer the corrected text
+CREATE OR REPLACE FUNCTION clear_ocr_text_correction() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.ocr_text IS DISTINCT FROM OLD.ocr_text
+       AND NEW.ocr_text_corrected_at IS NOT DISTINCT FROM OLD.ocr_text_corrected_at THEN
+        NEW.ocr_text_corrected_at := NULL;
+        NEW.ocr_text_corrected_by := NULL;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS documents_clear_ocr_text_correction ON documents;
+CREATE TRIGGER documents_clear_ocr_text_correction
+    BEFORE UPDATE OF ocr_text ON documents
+    FOR EACH ROW EXECUTE FUNCTION clear_ocr_text_correction();
@@ -0,0 +1,72 @@
+-- Text search configuration for an ISO 639-1 language code ("en", "de", ...);
+-- languages without a stemmer fold only
+CREATE OR REPLACE FUNCTION readur_translation_config(language_code TEXT)
+RETURNS regconfig AS $$
+    SELECT (CASE language_code
+        WHEN 'en' THEN 'readur_english'
+        WHEN 'de' THEN 'readur_german'
+        WHEN 'fr' THEN 'readur_french'
+        WHEN 'es' THEN 'readur_spanish'
+        WHEN 'it' THEN 'readur_italian'
+        WHEN 'pt' THEN 'readur_portuguese'
+        WHEN 'nl' THEN 'readur_dutch'
+        WHEN 'da' THEN 'readur_danish'
+        WHEN 'no' THEN 'readur_norwegian'
+        WHEN 'sv' THEN 'readur_swedish'
+        WHEN 'fi' THEN 'readur_finnish'
+        WHEN 'hu' THEN 'readur_hungarian'
+        WHEN 'ro' THEN 'readur_romanian'
+        WHEN 'ru' THEN 'readur_russian'
+        WHEN 'tr' THEN 'readur_turkish'
+        ELSE 'readur_simple'
+    END)::regconfig
+$$ LANGUAGE sql STABLE;
+
+-- Machine translations of a document's text, one per target language, indexed with
+-- the rules of their own language
+CREATE TABLE IF NOT EXISTS document_translations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    -- ISO 639-1 code of the target language
+    language TEXT NOT NULL,
+    text TEXT NOT NULL,
+    -- 'llm' or 'api', and the model for the LLM
+    provider TEXT NOT NULL CHECK (provider IN ('llm', 'api')),
+    model TEXT,
+    -- Characters of the document's text that were translated
+    source_chars INTEGER NOT NULL DEFAULT 0,
+    search_vector tsvector,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (document_id, language)
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_translations_language ON document_translations(language);
+CREATE INDEX IF NOT EXISTS idx_document_translations_search ON document_translations USING GIN(search_vector);
+
+CREATE OR REPLACE FUNCTION update_translation_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector := readur_search_vector(NEW.text, readur_translation_config(NEW.language));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_translation_search_vector ON document_translations;
+CREATE TRIGGER trigger_update_translation_search_vector
+    BEFORE INSERT OR UPDATE OF text, language ON document_translations
+    FOR EACH ROW EXECUTE FUNCTION update_translation_search_vector();
+
+-- As before, plus the configurations translations are indexed with, so that search
+-- queries are also parsed with their rules
+CREATE OR REPLACE FUNCTION readur_search_configs()
+RETURNS regconfig[] AS $$
+    SELECT array_agg(DISTINCT config) FROM (
+        SELECT 'readur_english'::regconfig AS config
+        UNION
+        SELECT readur_search_config(COALESCE(primary_language, ocr_language)) FROM settings
+        UNION
+        SELECT readur_translation_config(language) FROM (SELECT DISTINCT language FROM document_translations) languages
+    ) configs
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,21 @@
+-- Spoken narrations of a document's text made with a text-to-speech API, one per
+-- document. The audio file is kept through regenerations until a new one is written.
+CREATE TABLE IF NOT EXISTS document_audio (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID NOT NULL UNIQUE REFERENCES documents(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'completed', 'failed')),
+    voice TEXT NOT NULL,
+    model TEXT NOT NULL,
+    -- Characters of the document's text that are narrated
+    source_chars INTEGER NOT NULL DEFAULT 0,
+    file_path TEXT,
+    file_size BIGINT,
+    error TEXT,
+    requested_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_audio_unfinished ON document_audio(created_at)
+    WHERE status IN ('queued', 'running');
@@ -0,0 +1,26 @@
+-- External commands and webhooks run around ingestion: pre-consume hooks see a file
+-- before it is stored and may replace or reject it, post-consume hooks are told
+-- about a document once OCR is done
+CREATE TABLE IF NOT EXISTS ingestion_hooks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    stage TEXT NOT NULL CHECK (stage IN ('pre_consume', 'post_consume')),
+    kind TEXT NOT NULL CHECK (kind IN ('command', 'webhook')),
+    -- Executable in INGESTION_HOOKS_DIR for commands, http(s) URL for webhooks
+    target TEXT NOT NULL,
+    timeout_seconds INTEGER NOT NULL DEFAULT 30 CHECK (timeout_seconds > 0),
+    -- Whether a pre-consume hook that cannot run rejects the file or lets it through
+    on_failure TEXT NOT NULL DEFAULT 'reject' CHECK (on_failure IN ('reject', 'ignore')),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Hooks of a stage run in ascending position
+    position INTEGER NOT NULL DEFAULT 0,
+    -- Key webhook requests are signed with
+    secret TEXT,
+    last_run_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingestion_hooks_stage ON ingestion_hooks(stage, position) WHERE enabled;
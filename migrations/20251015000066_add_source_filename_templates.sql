@@ -0,0 +1,8 @@
+-- Templates like '{date}_{correspondent}_{title}.pdf' that read the date, correspondent,
+-- title, document type and tags of a source's documents from their file names at
+-- ingestion. The first template that matches a file name applies.
+CREATE TABLE IF NOT EXISTS source_filename_templates (
+    source_id UUID PRIMARY KEY REFERENCES sources(id) ON DELETE CASCADE,
+    templates TEXT[] NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,34 @@
+-- Admin-defined layout for stored document files, e.g. `{year}/{correspondent}/{title}-{id}.pdf`,
+-- rendered under the backend's `library/` prefix. Without a row files keep the backend's own
+-- layout by document id.
+CREATE TABLE IF NOT EXISTS storage_layout (
+    -- Single row
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    template TEXT NOT NULL,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Runs that move existing files into the current layout after it changed. Documents are
+-- processed in id order, so an interrupted run continues after `last_document_id`.
+CREATE TABLE IF NOT EXISTS storage_relocations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- NULL moves files back to the backend's own layout
+    template TEXT,
+    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'failed')),
+    total_documents BIGINT NOT NULL DEFAULT 0,
+    moved_documents BIGINT NOT NULL DEFAULT 0,
+    skipped_documents BIGINT NOT NULL DEFAULT 0,
+    failed_documents BIGINT NOT NULL DEFAULT 0,
+    last_document_id UUID,
+    -- The first failures: [{"document_id", "source_path", "error"}]
+    failures JSONB NOT NULL DEFAULT '[]',
+    error TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- At most one relocation runs at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_storage_relocations_single_running
+    ON storage_relocations ((status)) WHERE status = 'running';
@@ -0,0 +1,38 @@
+-- Failed documents can be requeued once the problem behind them is fixed. A requeued
+-- failure stays on record for the document's failure history.
+ALTER TABLE failed_documents
+    ADD COLUMN IF NOT EXISTS requeued_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS requeued_document_id UUID REFERENCES documents(id) ON DELETE SET NULL;
+
+-- Storage and database failures were recorded as 'storage_error' and 'database_error',
+-- which the check did not allow
+ALTER TABLE failed_documents DROP CONSTRAINT IF EXISTS check_failure_reason;
+ALTER TABLE failed_documents ADD CONSTRAINT check_failure_reason CHECK (failure_reason IN (
+    'duplicate_content',
+    'duplicate_filename',
+    'unsupported_format',
+    'file_too_large',
+    'file_corrupted',
+    'access_denied',
+    'low_ocr_confidence',
+    'ocr_timeout',
+    'ocr_memory_limit',
+    'pdf_parsing_error',
+    'storage_quota_exceeded',
+    'storage_error',
+    'database_error',
+    'network_error',
+    'permission_denied',
+    'virus_detected',
+    'invalid_structure',
+    'policy_violation',
+    'other'
+));
+
+CREATE INDEX IF NOT EXISTS idx_failed_documents_open
+    ON failed_documents(user_id, created_at DESC) WHERE requeued_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_failed_documents_existing_document
+    ON failed_documents(existing_document_id) WHERE existing_document_id IS NOT NULL;
+
+COMMENT ON COLUMN failed_documents.requeued_at IS 'When the failure was requeued for another attempt';
+COMMENT ON COLUMN failed_documents.requeued_document_id IS 'The document the requeued attempt processed or created';
@@ -0,0 +1,19 @@
+-- Scanners and multifunction printers that deliver scans over FTP. Each device signs
+-- in with its own generated username and password, so one can be revoked without
+-- touching the others; only a hash of the password is kept.
+CREATE TABLE IF NOT EXISTS scan_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    username TEXT NOT NULL,
+    password_hash TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    documents_received BIGINT NOT NULL DEFAULT 0,
+    last_used_at TIMESTAMPTZ,
+    last_address TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_scan_devices_username ON scan_devices(LOWER(username));
+CREATE INDEX IF NOT EXISTS idx_scan_devices_user ON scan_devices(user_id);
@@ -0,0 +1,48 @@
+-- Evaluations of two OCR engines on the same sample of documents. `engine_a` and
+-- `engine_b` hold the engine and its settings, e.g.
+-- {"engine": "tesseract", "page_segmentation_mode": 6}
+CREATE TABLE IF NOT EXISTS ocr_comparisons (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    engine_a JSONB NOT NULL,
+    engine_b JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued',
+    total_documents INT NOT NULL DEFAULT 0,
+    processed_documents INT NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ocr_comparisons_created ON ocr_comparisons(created_at DESC);
+
+-- The sample of a comparison: each document with the class it is reported under,
+-- the text it is scored against when there is one, and what both engines made of it.
+-- `agreement` is the character accuracy of engine B's text measured against engine
+-- A's, for documents without a reference text.
+CREATE TABLE IF NOT EXISTS ocr_comparison_documents (
+    comparison_id UUID NOT NULL REFERENCES ocr_comparisons(id) ON DELETE CASCADE,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    document_class TEXT NOT NULL,
+    ground_truth TEXT,
+    text_a TEXT,
+    confidence_a REAL,
+    duration_ms_a BIGINT,
+    error_a TEXT,
+    character_accuracy_a DOUBLE PRECISION,
+    word_accuracy_a DOUBLE PRECISION,
+    text_b TEXT,
+    confidence_b REAL,
+    duration_ms_b BIGINT,
+    error_b TEXT,
+    character_accuracy_b DOUBLE PRECISION,
+    word_accuracy_b DOUBLE PRECISION,
+    agreement DOUBLE PRECISION,
+    processed_at TIMESTAMPTZ,
+    PRIMARY KEY (comparison_id, document_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ocr_comparison_documents_position ON ocr_comparison_documents(comparison_id, position);
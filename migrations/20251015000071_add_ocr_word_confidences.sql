@@ -0,0 +1,13 @@
+-- Every word Tesseract recognized on a document page, with its confidence and pixel
+-- box on the page as rendered, so uncertain words can be marked for correction.
+-- `words` holds [{"text": "Invoice", "confidence": 91.5, "bbox": {"x": 120, "y": 88, "width": 140, "height": 32}}, ...]
+-- in reading order.
+CREATE TABLE IF NOT EXISTS ocr_page_words (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_number INTEGER NOT NULL CHECK (page_number >= 1),
+    page_width INTEGER NOT NULL,
+    page_height INTEGER NOT NULL,
+    words JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (document_id, page_number)
+);
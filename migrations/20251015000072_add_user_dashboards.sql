@@ -0,0 +1,11 @@
+-- The statistics widgets each user arranged on their dashboard, e.g.
+-- [{"id": "uploads", "statistic": "ingested", "title": "Uploads", "bucket": "week", "days": 90}]
+CREATE TABLE IF NOT EXISTS user_dashboards (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    widgets JSONB NOT NULL DEFAULT '[]',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Statistics bucket documents by upload and OCR completion time
+CREATE INDEX IF NOT EXISTS idx_documents_created_at ON documents(created_at);
+CREATE INDEX IF NOT EXISTS idx_documents_ocr_completed_at ON documents(ocr_completed_at) WHERE ocr_completed_at IS NOT NULL;
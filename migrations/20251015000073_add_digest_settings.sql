@@ -0,0 +1,20 @@
+-- Scheduled email digests. A user with a daily or weekly digest gets a summary of
+-- the documents ingested, the jobs that failed and the saved searches that matched
+-- since the previous digest, sent to their email notification channels.
+CREATE TABLE IF NOT EXISTS digest_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    frequency TEXT NOT NULL DEFAULT 'off' CHECK (frequency IN ('off', 'daily', 'weekly')),
+    -- Hour of the day (UTC) the digest goes out
+    send_hour SMALLINT NOT NULL DEFAULT 7 CHECK (send_hour BETWEEN 0 AND 23),
+    -- Day of the week of weekly digests, 0 = Monday
+    weekday SMALLINT NOT NULL DEFAULT 0 CHECK (weekday BETWEEN 0 AND 6),
+    -- Send nothing when there is nothing to report
+    skip_empty BOOLEAN NOT NULL DEFAULT TRUE,
+    -- End of the period the latest digest covered
+    last_sent_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_digest_settings_enabled ON digest_settings(user_id) WHERE frequency <> 'off';
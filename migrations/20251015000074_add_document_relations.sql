@@ -0,0 +1,19 @@
+-- Typed links between documents, e.g. an amendment that amends a contract or a
+-- receipt that is the payment for an invoice. A relation reads from the source to
+-- the target: "<source> amends <target>".
+CREATE TABLE IF NOT EXISTS document_relations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    target_document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    relation_type TEXT NOT NULL CHECK (relation_type IN (
+        'related', 'references', 'amends', 'supersedes', 'payment_for', 'responds_to', 'attachment_of'
+    )),
+    note TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (source_document_id <> target_document_id),
+    UNIQUE (source_document_id, target_document_id, relation_type)
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_relations_target ON document_relations(target_document_id);
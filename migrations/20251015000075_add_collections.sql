@@ -0,0 +1,87 @@
+-- User-curated groupings of documents, e.g. a case file or a project binder. Unlike
+-- labels, a collection keeps its documents in a chosen order and can contain
+-- subcollections. Every collection of a tree belongs to the owner of its root.
+CREATE TABLE IF NOT EXISTS collections (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    parent_id UUID REFERENCES collections(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    -- Order among the collections of the same parent
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (parent_id <> id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_collections_user ON collections(user_id);
+CREATE INDEX IF NOT EXISTS idx_collections_parent ON collections(parent_id) WHERE parent_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS collection_documents (
+    collection_id UUID NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL DEFAULT 0,
+    added_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (collection_id, document_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_collection_documents_document ON collection_documents(document_id);
+
+-- A collection shared with a user or a group, covering its subcollections and every
+-- document of the collection's owner in them
+CREATE TABLE IF NOT EXISTS collection_shares (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    collection_id UUID NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    group_id UUID REFERENCES user_groups(id) ON DELETE CASCADE,
+    permission share_permission NOT NULL DEFAULT 'view',
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((user_id IS NULL) <> (group_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_collection_shares_user ON collection_shares(collection_id, user_id) WHERE user_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_collection_shares_group ON collection_shares(collection_id, group_id) WHERE group_id IS NOT NULL;
+
+-- Every (document, user, permission) granted through shares, directly, by group
+-- membership, through a label or through a collection. A user may appear several
+-- times for one document; take the highest.
+CREATE OR REPLACE VIEW shared_document_access AS
+WITH RECURSIVE shared_collections AS (
+    SELECT c.id AS collection_id, c.user_id AS owner_id, s.user_id, s.group_id, s.permission
+    FROM collection_shares s
+    JOIN collections c ON c.id = s.collection_id
+    UNION
+    SELECT c.id, sc.owner_id, sc.user_id, sc.group_id, sc.permission
+    FROM collections c
+    JOIN shared_collections sc ON c.parent_id = sc.collection_id
+)
+SELECT s.document_id, s.user_id, s.permission
+FROM document_shares s
+WHERE s.user_id IS NOT NULL
+UNION ALL
+SELECT s.document_id, m.user_id, s.permission
+FROM document_shares s
+JOIN user_group_members m ON m.group_id = s.group_id
+UNION ALL
+SELECT dl.document_id, grantee.user_id, s.permission
+FROM label_shares s
+JOIN labels l ON l.id = s.label_id
+JOIN document_labels dl ON dl.label_id = s.label_id
+JOIN documents d ON d.id = dl.document_id AND d.user_id = l.user_id
+JOIN LATERAL (
+    SELECT s.user_id WHERE s.user_id IS NOT NULL
+    UNION ALL
+    SELECT m.user_id FROM user_group_members m WHERE m.group_id = s.group_id
+) grantee ON TRUE
+UNION ALL
+SELECT cd.document_id, grantee.user_id, sc.permission
+FROM shared_collections sc
+JOIN collection_documents cd ON cd.collection_id = sc.collection_id
+JOIN documents d ON d.id = cd.document_id AND d.user_id = sc.owner_id
+JOIN LATERAL (
+    SELECT sc.user_id WHERE sc.user_id IS NOT NULL
+    UNION ALL
+    SELECT m.user_id FROM user_group_members m WHERE m.group_id = sc.group_id
+) grantee ON TRUE;
@@ -0,0 +1,36 @@
+-- Optical mark recognition: admins draw checkbox and field regions on a page of a
+-- blank form. Scanned documents that match a template get their marks and filled-in
+-- fields read into structured data.
+CREATE TABLE IF NOT EXISTS omr_templates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    -- 1-based page of the scanned form the regions are on
+    page INTEGER NOT NULL DEFAULT 1 CHECK (page >= 1),
+    -- Phrases identifying the form in OCR text, e.g. its title or form number; a
+    -- document matches when it contains all of them
+    match_phrases TEXT[] NOT NULL DEFAULT '{}',
+    -- Ordered list of {key, label, kind, x, y, width, height, group, value}, with the
+    -- rectangle as fractions of the page's width and height
+    regions JSONB NOT NULL DEFAULT '[]',
+    -- Share of dark pixels inside a checkbox from which it counts as marked
+    fill_threshold REAL NOT NULL DEFAULT 0.15 CHECK (fill_threshold > 0 AND fill_threshold < 1),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS omr_results (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    template_id UUID NOT NULL REFERENCES omr_templates(id) ON DELETE CASCADE,
+    -- Field key to value: true or false for checkboxes, the marked option(s) of a
+    -- checkbox group, the text of a field
+    data JSONB NOT NULL DEFAULT '{}',
+    -- What was read in each region: fill ratio and mark of checkboxes, text of fields
+    readings JSONB NOT NULL DEFAULT '[]',
+    extracted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_omr_results_template ON omr_results(template_id, extracted_at DESC);
+CREATE INDEX IF NOT EXISTS idx_omr_results_data ON omr_results USING GIN (data);
@@ -0,0 +1,16 @@
+-- Answers of the chat model, so analysing text that has not changed again costs
+-- nothing. Keyed by what was asked (the prompt's task and version), of which model,
+-- and a hash of the exact input; a new prompt version or model misses the cache.
+CREATE TABLE IF NOT EXISTS llm_response_cache (
+    -- Prompt the answer is for, e.g. 'graph_extraction' or 'pii'
+    task TEXT NOT NULL,
+    prompt_version INTEGER NOT NULL,
+    model TEXT NOT NULL,
+    -- SHA-256 of the system prompt and the prompt sent
+    content_hash TEXT NOT NULL,
+    response TEXT NOT NULL,
+    hit_count BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_hit_at TIMESTAMPTZ,
+    PRIMARY KEY (task, prompt_version, model, content_hash)
+);
@@ -0,0 +1,11 @@
+-- Limits set by admins at runtime on requests readur sends to the LLM and to the
+-- servers sources sync from. A scope without a row uses the limits from the
+-- environment; NULL columns mean no limit of that kind.
+CREATE TABLE IF NOT EXISTS outbound_limits (
+    scope TEXT PRIMARY KEY CHECK (scope IN ('global', 'llm', 'webdav', 'onedrive', 'dropbox')),
+    max_concurrent INTEGER CHECK (max_concurrent >= 1),
+    requests_per_minute INTEGER CHECK (requests_per_minute >= 1),
+    burst INTEGER CHECK (burst >= 1),
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,16 @@
+-- Thumbnails and office previews generated in the background after ingestion, behind
+-- OCR, so browsing a library does not wait for each one to render on first view.
+-- Existing documents are queued by the backfill an admin starts.
+CREATE TABLE IF NOT EXISTS thumbnail_jobs (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'processing', 'completed', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    -- When a pending job may be claimed; pushed back after a failed attempt
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_thumbnail_jobs_due ON thumbnail_jobs(next_attempt_at)
+    WHERE status = 'pending';
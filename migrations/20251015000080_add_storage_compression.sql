@@ -0,0 +1,53 @@
+-- Storage policies recompressing oversized scans once OCR is done, e.g. 600 dpi color
+-- TIFFs into PDFs of JPEG pages at a lower resolution. The true original is kept for a
+-- while in case it is needed, then deleted.
+CREATE TABLE IF NOT EXISTS storage_compression_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL UNIQUE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Types of the files the policy applies to, e.g. image/tiff or application/pdf
+    mime_types TEXT[] NOT NULL,
+    -- Only files at least this large are recompressed
+    min_file_size_bytes BIGINT NOT NULL DEFAULT 0 CHECK (min_file_size_bytes >= 0),
+    target_format TEXT NOT NULL CHECK (target_format IN ('pdf', 'jpeg')),
+    -- Resolution pages are downsampled to
+    target_dpi INTEGER NOT NULL DEFAULT 200 CHECK (target_dpi BETWEEN 72 AND 600),
+    jpeg_quality INTEGER NOT NULL DEFAULT 75 CHECK (jpeg_quality BETWEEN 10 AND 100),
+    -- Days the original can still be downloaded; NULL keeps it until the document is deleted
+    keep_original_days INTEGER CHECK (keep_original_days >= 0),
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Originals of recompressed documents. The file is stored under its own id, like a
+-- document version, and deleted once the window to retrieve it ends.
+CREATE TABLE IF NOT EXISTS compressed_originals (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    id UUID NOT NULL UNIQUE,
+    policy_id UUID REFERENCES storage_compression_policies(id) ON DELETE SET NULL,
+    -- Name the file is stored under, and the name it was uploaded with
+    filename TEXT NOT NULL,
+    original_filename TEXT NOT NULL,
+    -- NULL once the file was deleted
+    file_path TEXT,
+    file_size BIGINT NOT NULL,
+    file_hash TEXT,
+    mime_type TEXT NOT NULL,
+    compressed_size BIGINT NOT NULL,
+    compressed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    purged_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_compressed_originals_expiry ON compressed_originals(expires_at)
+    WHERE purged_at IS NULL AND expires_at IS NOT NULL;
+
+-- Documents recompression was tried on without making them smaller, or whose file
+-- cannot be recompressed, so they are not tried again until their content changes
+CREATE TABLE IF NOT EXISTS storage_compression_skips (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    policy_id UUID REFERENCES storage_compression_policies(id) ON DELETE SET NULL,
+    file_hash TEXT,
+    skipped_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,32 @@
+-- Processes sharing the database, e.g. an API server with dedicated OCR, analysis and
+-- sync workers. Each process registers under a new id when it starts and keeps its row
+-- current with a heartbeat.
+CREATE TABLE IF NOT EXISTS worker_nodes (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    hostname TEXT NOT NULL,
+    -- Work the process takes on: api, ocr, analysis, sync and maintenance
+    roles TEXT[] NOT NULL,
+    version TEXT NOT NULL,
+    -- Id the process claims OCR queue items under, when it runs OCR
+    ocr_worker_id TEXT,
+    active_ocr_jobs INTEGER NOT NULL DEFAULT 0,
+    max_ocr_jobs INTEGER NOT NULL DEFAULT 0,
+    -- Background tasks that run on one node at a time and currently run on this one
+    exclusive_tasks TEXT[] NOT NULL DEFAULT '{}',
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_heartbeat_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    stopped_at TIMESTAMPTZ
+);
+
+-- Nodes that reported within the last minute; work claimed by any other node is
+-- taken to be abandoned
+CREATE OR REPLACE VIEW live_worker_nodes AS
+    SELECT * FROM worker_nodes
+    WHERE stopped_at IS NULL AND last_heartbeat_at > NOW() - INTERVAL '60 seconds';
+
+-- Node running a source's sync, so a restarting node does not reset syncs of others
+ALTER TABLE sources ADD COLUMN IF NOT EXISTS sync_node_id UUID;
+
+-- Node running an analysis batch; batches of nodes that are gone are taken over
+ALTER TABLE analysis_batches ADD COLUMN IF NOT EXISTS node_id UUID;
@@ -0,0 +1,11 @@
+-- Text of the pages OCRed so far for a document whose OCR runs page by page. A job
+-- interrupted by a shutdown continues from the first page missing here; the rows are
+-- removed once the job completes or fails.
+CREATE TABLE IF NOT EXISTS ocr_page_checkpoints (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_number INTEGER NOT NULL CHECK (page_number >= 1),
+    text TEXT NOT NULL,
+    confidence REAL NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (document_id, page_number)
+);
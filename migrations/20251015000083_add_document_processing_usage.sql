@@ -0,0 +1,21 @@
+-- What processing each document took: one row per OCR run and per LLM request made for
+-- it, so admins can find documents that are unusually expensive and estimate what
+-- cloud OCR or a paid LLM would cost. Storage is read from the documents themselves.
+CREATE TABLE IF NOT EXISTS document_processing_usage (
+    id BIGSERIAL PRIMARY KEY,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('ocr', 'llm')),
+    -- Time the OCR run or LLM request took
+    seconds DOUBLE PRECISION NOT NULL DEFAULT 0,
+    -- Pages of the file an OCR run went through
+    pages INTEGER NOT NULL DEFAULT 0,
+    -- Model and tokens the LLM API reported, and their price when token prices are set
+    model TEXT,
+    prompt_tokens BIGINT NOT NULL DEFAULT 0,
+    completion_tokens BIGINT NOT NULL DEFAULT 0,
+    cost DOUBLE PRECISION,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_processing_usage_document ON document_processing_usage(document_id);
+CREATE INDEX IF NOT EXISTS idx_document_processing_usage_created ON document_processing_usage(created_at);
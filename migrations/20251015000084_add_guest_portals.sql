@@ -0,0 +1,21 @@
+-- Guest portals: a label or collection an admin exposes read-only to visitors without
+-- an account, e.g. a public records archive. Visitors only search and download the
+-- documents of the portal, rate limited per address.
+CREATE TABLE IF NOT EXISTS guest_portals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    slug TEXT NOT NULL UNIQUE CHECK (slug ~ '^[a-z0-9][a-z0-9-]{0,62}$'),
+    title TEXT NOT NULL,
+    description TEXT,
+    label_id UUID REFERENCES labels(id) ON DELETE CASCADE,
+    -- Subcollections are exposed with their collection
+    collection_id UUID REFERENCES collections(id) ON DELETE CASCADE,
+    allow_download BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Whether visitors see and search the OCR text, or only file names
+    show_text BOOLEAN NOT NULL DEFAULT TRUE,
+    requests_per_minute INTEGER NOT NULL DEFAULT 30 CHECK (requests_per_minute BETWEEN 1 AND 10000),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((label_id IS NULL) <> (collection_id IS NULL))
+);
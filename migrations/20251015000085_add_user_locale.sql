@@ -0,0 +1,3 @@
+-- Language of the notifications, digests and error messages a user gets, such as
+-- 'de' or 'pt-BR'. NULL follows DEFAULT_LOCALE.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locale TEXT;
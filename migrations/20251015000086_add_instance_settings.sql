@@ -0,0 +1,15 @@
+-- Branding and defaults of this Readur instance, shown on the login page before anyone
+-- signs in. Without a row the built-in name and look are used.
+CREATE TABLE IF NOT EXISTS instance_settings (
+    -- Single row
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    name TEXT,
+    logo_url TEXT,
+    -- e.g. #0969da
+    accent_color TEXT,
+    login_message TEXT,
+    -- Locale for users who chose none, before DEFAULT_LOCALE
+    default_language TEXT,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
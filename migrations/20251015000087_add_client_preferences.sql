@@ -0,0 +1,11 @@
+-- View settings of the web interface and other clients, kept per user so they follow the
+-- user across devices. Each client owns namespaces such as `web.document-list` and
+-- versions the schema of their values; the server stores the JSON as it is.
+CREATE TABLE IF NOT EXISTS client_preferences (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    namespace TEXT NOT NULL,
+    schema_version INTEGER NOT NULL DEFAULT 1 CHECK (schema_version >= 1),
+    value JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, namespace)
+);
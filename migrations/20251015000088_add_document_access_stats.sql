@@ -0,0 +1,30 @@
+-- Views and downloads of documents per day and user, for access statistics. Kept apart
+-- from the audit log, which may be pruned and holds one row per request.
+CREATE TABLE IF NOT EXISTS document_access_daily (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    -- NULL for visitors of share links and guest portals
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    -- UTC
+    day DATE NOT NULL,
+    views BIGINT NOT NULL DEFAULT 0,
+    downloads BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_document_access_daily_key
+    ON document_access_daily (document_id, COALESCE(user_id, '00000000-0000-0000-0000-000000000000'::uuid), day);
+CREATE INDEX IF NOT EXISTS idx_document_access_daily_day ON document_access_daily (day);
+CREATE INDEX IF NOT EXISTS idx_document_access_daily_user ON document_access_daily (user_id, day);
+
+-- Start from the views and downloads the audit log still has
+INSERT INTO document_access_daily (document_id, user_id, day, views, downloads)
+SELECT a.resource_id,
+       u.id,
+       (a.created_at AT TIME ZONE 'UTC')::date,
+       COUNT(*) FILTER (WHERE a.action = 'document.view'),
+       COUNT(*) FILTER (WHERE a.action = 'document.download')
+FROM audit_log a
+JOIN documents d ON d.id = a.resource_id
+LEFT JOIN users u ON u.id = a.user_id
+WHERE a.resource_type = 'document' AND a.action IN ('document.view', 'document.download')
+GROUP BY a.resource_id, u.id, (a.created_at AT TIME ZONE 'UTC')::date
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,16 @@
+-- Titles generated from the contents of documents that arrived with names like
+-- SCAN_0001.pdf. The document is renamed to the title; the name it had before is kept
+-- here, so the title can be undone.
+CREATE TABLE IF NOT EXISTS document_titles (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    -- The name before the first generated title
+    original_filename TEXT NOT NULL,
+    title TEXT NOT NULL,
+    generated_by TEXT NOT NULL CHECK (generated_by IN ('llm', 'heuristic')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Bulk operations can retitle existing documents
+ALTER TABLE bulk_operations DROP CONSTRAINT IF EXISTS bulk_operations_operation_check;
+ALTER TABLE bulk_operations ADD CONSTRAINT bulk_operations_operation_check
+    CHECK (operation IN ('add_labels', 'remove_labels', 'delete', 'reprocess_ocr', 'change_owner', 'merge', 'retitle'));
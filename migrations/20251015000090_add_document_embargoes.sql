@@ -0,0 +1,28 @@
+-- Embargoed documents are hidden from everyone but admins and their owners: shares,
+-- workspaces, share links and guest portals do not reach them until the embargo date
+-- passes or an admin releases them. No job lifts embargoes: queries compare the date
+-- with NOW(), so a document shows up the moment it is due.
+CREATE TABLE IF NOT EXISTS document_embargoes (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    -- NULL keeps the document hidden until it is released by hand
+    embargoed_until TIMESTAMPTZ,
+    reason TEXT,
+    source_id UUID REFERENCES sources(id) ON DELETE SET NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    released_at TIMESTAMPTZ,
+    released_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_embargoes_active
+    ON document_embargoes(document_id) WHERE released_at IS NULL;
+
+-- Documents a source ingests are embargoed for a number of days, or until released
+CREATE TABLE IF NOT EXISTS source_embargoes (
+    source_id UUID PRIMARY KEY REFERENCES sources(id) ON DELETE CASCADE,
+    -- NULL embargoes the documents until they are released by hand
+    embargo_days INTEGER CHECK (embargo_days IS NULL OR embargo_days > 0),
+    reason TEXT,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,17 @@
+-- How each page of a scan is turned upright: a rotation by quarter turns, then a small
+-- deskew. Detected before OCR or set by hand, and applied alike by OCR, page images,
+-- thumbnails and generated searchable PDFs. The stored file itself is not changed.
+CREATE TABLE IF NOT EXISTS document_page_transforms (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_number INTEGER NOT NULL CHECK (page_number > 0),
+    -- Clockwise, in degrees
+    rotation INTEGER NOT NULL DEFAULT 0 CHECK (rotation IN (0, 90, 180, 270)),
+    -- Counter-clockwise correction applied after the rotation, in degrees
+    skew_degrees REAL NOT NULL DEFAULT 0,
+    source TEXT NOT NULL CHECK (source IN ('auto', 'manual')),
+    -- Orientation confidence reported by the detection
+    confidence REAL,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (document_id, page_number)
+);
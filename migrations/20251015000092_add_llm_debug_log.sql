@@ -0,0 +1,37 @@
+-- Opt-in log of the prompts sent to the LLM during analysis runs and the answers that
+-- came back, for looking into failed or odd graph extractions without trace logging.
+-- Personal data is redacted as configured before anything is stored, and runs are
+-- removed once they are older than the retention period.
+CREATE TABLE IF NOT EXISTS llm_debug_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_id UUID REFERENCES documents(id) ON DELETE CASCADE,
+    task TEXT NOT NULL,
+    model TEXT NOT NULL,
+    -- Graph version the run produced; NULL while running or when it failed
+    graph_version_id UUID REFERENCES document_graph_versions(id) ON DELETE SET NULL,
+    error TEXT,
+    -- Kinds of personal data redacted from the stored text, e.g. 'email,phone', or 'none'
+    redaction TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_llm_debug_runs_document ON llm_debug_runs(document_id, started_at DESC);
+CREATE INDEX IF NOT EXISTS idx_llm_debug_runs_started ON llm_debug_runs(started_at);
+
+-- Each request of a run, in the order it was made
+CREATE TABLE IF NOT EXISTS llm_debug_exchanges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    run_id UUID NOT NULL REFERENCES llm_debug_runs(id) ON DELETE CASCADE,
+    prompt_version INTEGER NOT NULL,
+    system_prompt TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    response TEXT,
+    error TEXT,
+    -- Answered from the response cache instead of the model
+    cached BOOLEAN NOT NULL DEFAULT FALSE,
+    duration_ms BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_llm_debug_exchanges_run ON llm_debug_exchanges(run_id, created_at);
@@ -0,0 +1,15 @@
+-- Queues paused by an admin and the maintenance mode, shared by all nodes. Without a
+-- row every queue runs and the instance takes writes.
+CREATE TABLE IF NOT EXISTS operation_controls (
+    -- Single row
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    ocr_paused BOOLEAN NOT NULL DEFAULT FALSE,
+    sync_paused BOOLEAN NOT NULL DEFAULT FALSE,
+    analysis_paused BOOLEAN NOT NULL DEFAULT FALSE,
+    maintenance_mode BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Shown to clients whose writes are rejected
+    maintenance_message TEXT,
+    maintenance_started_at TIMESTAMPTZ,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,21 @@
+-- When contracts, certificates and other documents expire or renew. Dates are set by
+-- hand or found in the OCR text; a scheduler notifies the document's owner a number of
+-- days before, on the owner's notification channels.
+CREATE TABLE IF NOT EXISTS document_expirations (
+    document_id UUID PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    expires_on DATE NOT NULL,
+    -- 'expiration' when the document stops being valid, 'renewal' when it renews
+    -- unless cancelled before
+    kind TEXT NOT NULL DEFAULT 'expiration' CHECK (kind IN ('expiration', 'renewal')),
+    -- Days before expires_on the owner is notified
+    notice_days INTEGER NOT NULL CHECK (notice_days BETWEEN 0 AND 365),
+    note TEXT,
+    -- 'manual' or 'extracted'; extraction never replaces a date set by hand
+    source TEXT NOT NULL CHECK (source IN ('manual', 'extracted')),
+    notified_at TIMESTAMPTZ,
+    set_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_expirations_due ON document_expirations(expires_on) WHERE notified_at IS NULL;
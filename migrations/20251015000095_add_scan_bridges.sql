@@ -0,0 +1,40 @@
+-- Desktop bridge apps that push pages from directly attached (TWAIN, WIA, SANE)
+-- scanners. A bridge asks for a pairing code, the user approves the code while signed
+-- in, and the bridge then collects a token of its own; only a hash of it is kept.
+CREATE TABLE IF NOT EXISTS scan_bridges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    token_prefix TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    platform TEXT,
+    version TEXT,
+    pages_received BIGINT NOT NULL DEFAULT 0,
+    documents_received BIGINT NOT NULL DEFAULT 0,
+    last_used_at TIMESTAMPTZ,
+    last_address TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_scan_bridges_user ON scan_bridges(user_id);
+
+-- Pairings waiting for the user to approve the code, or for the bridge to collect its
+-- token. The bridge proves it started the pairing with the device code, of which only
+-- a hash is kept.
+CREATE TABLE IF NOT EXISTS scan_bridge_pairings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    device_code_hash TEXT NOT NULL UNIQUE,
+    user_code TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    platform TEXT,
+    version TEXT,
+    address TEXT,
+    approved_by UUID REFERENCES users(id) ON DELETE CASCADE,
+    approved_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_scan_bridge_pairings_expires ON scan_bridge_pairings(expires_at);
@@ -0,0 +1,16 @@
+-- Contents that made an external converter (LibreOffice, pdftoppm, pdftotext, ...) get
+-- killed for exceeding its CPU, memory, output or time limit. Once a content was
+-- killed often enough it is quarantined: no converter is started on it again until an
+-- admin releases it.
+CREATE TABLE IF NOT EXISTS converter_quarantine (
+    file_hash TEXT PRIMARY KEY,
+    tool TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    strikes INTEGER NOT NULL DEFAULT 1,
+    first_killed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_killed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    quarantined_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_converter_quarantine_quarantined ON converter_quarantine(quarantined_at)
+    WHERE quarantined_at IS NOT NULL;
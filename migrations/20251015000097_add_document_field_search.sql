@@ -0,0 +1,157 @@
+-- Text of a document that lives outside its body, indexed for search next to it:
+-- the text of its annotations, the comments on them and the values of its custom
+-- fields, one row per field. Rows are kept up to date by triggers on the tables the
+-- text comes from, and indexed with the rules of the owner's language, like the body.
+CREATE TABLE IF NOT EXISTS document_field_search (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    field TEXT NOT NULL CHECK (field IN ('annotations', 'comments', 'custom_fields')),
+    text TEXT NOT NULL,
+    search_vector tsvector,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (document_id, field)
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_field_search_vector ON document_field_search USING GIN(search_vector);
+
+-- Search vector of text belonging to a document, using the language its owner OCRs in
+CREATE OR REPLACE FUNCTION readur_document_field_search_vector(doc_id UUID, body TEXT)
+RETURNS tsvector AS $$
+    SELECT readur_search_vector(
+        body,
+        readur_search_config((
+            SELECT COALESCE(s.primary_language, s.ocr_language)
+            FROM documents d JOIN settings s ON s.user_id = d.user_id
+            WHERE d.id = doc_id
+        ))
+    )
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION update_document_field_search_vector()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector := readur_document_field_search_vector(NEW.document_id, NEW.text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_document_field_search_vector ON document_field_search;
+CREATE TRIGGER trigger_update_document_field_search_vector
+    BEFORE INSERT OR UPDATE OF text ON document_field_search
+    FOR EACH ROW EXECUTE FUNCTION update_document_field_search_vector();
+
+-- Recollect one field of a document from where its text lives. A field without text
+-- has no row; documents being deleted are left alone.
+CREATE OR REPLACE FUNCTION readur_refresh_document_field_search(doc_id UUID, field_name TEXT)
+RETURNS VOID AS $$
+DECLARE
+    collected TEXT;
+BEGIN
+    IF doc_id IS NULL OR NOT EXISTS (SELECT 1 FROM documents WHERE id = doc_id) THEN
+        RETURN;
+    END IF;
+
+    collected := CASE field_name
+        WHEN 'annotations' THEN (
+            SELECT string_agg(a.text, E'\n' ORDER BY a.page, a.created_at)
+            FROM document_annotations a
+            WHERE a.document_id = doc_id AND btrim(COALESCE(a.text, '')) <> ''
+        )
+        WHEN 'comments' THEN (
+            SELECT string_agg(c.body, E'\n' ORDER BY c.created_at)
+            FROM annotation_comments c JOIN document_annotations a ON a.id = c.annotation_id
+            WHERE a.document_id = doc_id AND btrim(c.body) <> ''
+        )
+        WHEN 'custom_fields' THEN (
+            SELECT string_agg(f.value, E'\n' ORDER BY f.key)
+            FROM document_custom_fields cf, jsonb_each_text(cf.fields) f
+            WHERE cf.document_id = doc_id AND btrim(COALESCE(f.value, '')) <> ''
+        )
+    END;
+
+    IF collected IS NULL THEN
+        DELETE FROM document_field_search WHERE document_id = doc_id AND field = field_name;
+    ELSE
+        INSERT INTO document_field_search (document_id, field, text)
+        VALUES (doc_id, field_name, collected)
+        ON CONFLICT (document_id, field) DO UPDATE
+            SET text = EXCLUDED.text, updated_at = NOW()
+            WHERE document_field_search.text IS DISTINCT FROM EXCLUDED.text;
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Annotations carry text of their own and the comments on them
+CREATE OR REPLACE FUNCTION refresh_annotation_field_search()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        PERFORM readur_refresh_document_field_search(OLD.document_id, 'annotations');
+        PERFORM readur_refresh_document_field_search(OLD.document_id, 'comments');
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        PERFORM readur_refresh_document_field_search(NEW.document_id, 'annotations');
+        IF TG_OP = 'UPDATE' AND NEW.document_id IS DISTINCT FROM OLD.document_id THEN
+            PERFORM readur_refresh_document_field_search(NEW.document_id, 'comments');
+        END IF;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_refresh_annotation_field_search ON document_annotations;
+CREATE TRIGGER trigger_refresh_annotation_field_search
+    AFTER INSERT OR DELETE OR UPDATE OF text, document_id ON document_annotations
+    FOR EACH ROW EXECUTE FUNCTION refresh_annotation_field_search();
+
+-- Comments whose annotation is already gone were handled by the annotation's trigger
+CREATE OR REPLACE FUNCTION refresh_comment_field_search()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        PERFORM readur_refresh_document_field_search(
+            (SELECT document_id FROM document_annotations WHERE id = OLD.annotation_id), 'comments'
+        );
+    END IF;
+    IF TG_OP <> 'DELETE' AND (TG_OP = 'INSERT' OR NEW.annotation_id IS DISTINCT FROM OLD.annotation_id OR NEW.body IS DISTINCT FROM OLD.body) THEN
+        PERFORM readur_refresh_document_field_search(
+            (SELECT document_id FROM document_annotations WHERE id = NEW.annotation_id), 'comments'
+        );
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_refresh_comment_field_search ON annotation_comments;
+CREATE TRIGGER trigger_refresh_comment_field_search
+    AFTER INSERT OR DELETE OR UPDATE OF body, annotation_id ON annotation_comments
+    FOR EACH ROW EXECUTE FUNCTION refresh_comment_field_search();
+
+CREATE OR REPLACE FUNCTION refresh_custom_field_search()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        PERFORM readur_refresh_document_field_search(OLD.document_id, 'custom_fields');
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        PERFORM readur_refresh_document_field_search(NEW.document_id, 'custom_fields');
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_refresh_custom_field_search ON document_custom_fields;
+CREATE TRIGGER trigger_refresh_custom_field_search
+    AFTER INSERT OR DELETE OR UPDATE OF fields, document_id ON document_custom_fields
+    FOR EACH ROW EXECUTE FUNCTION refresh_custom_field_search();
+
+-- Index what is there already
+SELECT readur_refresh_document_field_search(document_id, 'annotations')
+FROM (SELECT DISTINCT document_id FROM document_annotations WHERE text IS NOT NULL) annotated;
+
+SELECT readur_refresh_document_field_search(a.document_id, 'comments')
+FROM (
+    SELECT DISTINCT a.document_id
+    FROM annotation_comments c JOIN document_annotations a ON a.id = c.annotation_id
+) a;
+
+SELECT readur_refresh_document_field_search(document_id, 'custom_fields') FROM document_custom_fields;
@@ -0,0 +1,7 @@
+-- The admin who took an action while impersonating its user; null for everything else
+ALTER TABLE audit_log
+    ADD COLUMN IF NOT EXISTS impersonator_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS impersonator_username TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_impersonator ON audit_log(impersonator_id, created_at DESC)
+    WHERE impersonator_id IS NOT NULL;
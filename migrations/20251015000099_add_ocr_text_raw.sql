@@ -0,0 +1,20 @@
+-- OCR text as the OCR engine returned it, when normalization changed it before it
+-- was stored in ocr_text and indexed
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS ocr_text_raw TEXT;
+
+-- Text written by anything else (OCR resets, corrections, region OCR) no longer
+-- comes from that raw text
+CREATE OR REPLACE FUNCTION clear_ocr_text_raw() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.ocr_text IS DISTINCT FROM OLD.ocr_text
+       AND NEW.ocr_text_raw IS NOT DISTINCT FROM OLD.ocr_text_raw THEN
+        NEW.ocr_text_raw := NULL;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS documents_clear_ocr_text_raw ON documents;
+CREATE TRIGGER documents_clear_ocr_text_raw
+    BEFORE UPDATE OF ocr_text ON documents
+    FOR EACH ROW EXECUTE FUNCTION clear_ocr_text_raw();
@@ -0,0 +1,9 @@
+-- A date from a text of the form YYYY-MM-DD, or NULL when it is not a real date
+-- such as 2024-13-45, so metadata values can be compared as dates in searches
+CREATE OR REPLACE FUNCTION readur_try_date(value TEXT) RETURNS DATE AS $$
+BEGIN
+    RETURN value::date;
+EXCEPTION WHEN invalid_datetime_format OR datetime_field_overflow THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql STABLE;
@@ -0,0 +1,21 @@
+-- Workspaces (MULTI_TENANT_MODE) get data encryption keys of their own, which seal
+-- the workspace's documents and source credentials. Personal data keeps the key of
+-- its owner, so every key belongs to either a user or a workspace.
+ALTER TABLE encryption_keys ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces(id) ON DELETE CASCADE;
+ALTER TABLE encryption_keys ALTER COLUMN user_id DROP NOT NULL;
+
+ALTER TABLE encryption_keys DROP CONSTRAINT IF EXISTS encryption_keys_one_owner;
+ALTER TABLE encryption_keys ADD CONSTRAINT encryption_keys_one_owner
+    CHECK ((user_id IS NULL) <> (workspace_id IS NULL));
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_encryption_keys_workspace_version
+    ON encryption_keys(workspace_id, version) WHERE workspace_id IS NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_encryption_keys_one_active_workspace
+    ON encryption_keys(workspace_id) WHERE status = 'active' AND workspace_id IS NOT NULL;
+
+ALTER TABLE encryption_key_events ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces(id) ON DELETE CASCADE;
+ALTER TABLE encryption_key_events ALTER COLUMN user_id DROP NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_encryption_key_events_workspace_created
+    ON encryption_key_events(workspace_id, created_at DESC) WHERE workspace_id IS NOT NULL;
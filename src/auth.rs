@@ -1,16 +1,34 @@
 use anyhow::Result;
 use axum::{
-    extract::FromRequestParts,
-    http::{request::Parts, HeaderMap, StatusCode},
+    extract::{FromRequestParts, OriginalUri},
+    http::{
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        request::Parts,
+        HeaderMap, StatusCode,
+    },
     response::{IntoResponse, Response},
 };
+use base64ct::{Base64, Encoding};
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::{models::User, AppState};
+use crate::{
+    models::{Impersonator, ScanBridge, User, UserRole, UserSession, IMPERSONATION_METHOD},
+    services::{audit_service::ClientInfo, scan_bridge_service, workspace_service},
+    AppState,
+};
+
+/// Marks personal access tokens, so they are not mistaken for JWTs
+pub const API_TOKEN_PREFIX: &str = "rdr_";
+/// Characters of a token kept in the clear to recognise it
+const API_TOKEN_DISPLAY_LENGTH: usize = 12;
+/// Lifetime of a login session and its JWT
+pub const SESSION_HOURS: i64 = 24;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -19,8 +37,24 @@ pub struct Claims {
     pub exp: usize,
 }
 
+/// Claims of a JWT together with the session it belongs to. Tokens from `create_jwt`
+/// have no session and are only bounded by their expiry.
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionClaims {
+    #[serde(flatten)]
+    claims: Claims,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sid: Option<Uuid>,
+    /// The admin acting as the user, on impersonation tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    imp: Option<Impersonator>,
+}
+
 pub struct AuthUser {
     pub user: User,
+    /// The workspace named by the `X-Workspace-Id` header in multi-tenant mode. The user
+    /// is a member of it, or an admin.
+    pub workspace_id: Option<Uuid>,
 }
 
 impl FromRequestParts<Arc<AppState>> for AuthUser {
@@ -33,31 +67,271 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
         let headers = &parts.headers;
         let token = extract_token_from_headers(headers)
             .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Missing authorization header").into_response())?;
+        // Nested routers see their own part of the path; scopes are written against the full one
+        let path = parts
+            .extensions
+            .get::<OriginalUri>()
+            .map(|uri| uri.0.path().to_string())
+            .unwrap_or_else(|| parts.uri.path().to_string());
 
-        let claims = verify_jwt(&token, &state.config.jwt_secret)
-            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token").into_response())?;
+        let user_id = if token.starts_with(API_TOKEN_PREFIX) {
+            authenticate_api_token(state, &token, &parts.method, &path).await?
+        } else {
+            let claims = authenticate_session(state, &token).await?;
+            if claims.imp.as_ref().is_some_and(|impersonator| !impersonator.allows(&parts.method, &path)) {
+                return Err((StatusCode::FORBIDDEN, "Not allowed while impersonating").into_response());
+            }
+            claims.claims.sub
+        };
 
         let user = state
             .db
-            .get_user_by_id(claims.sub)
+            .get_user_by_id(user_id)
             .await
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())?
             .ok_or_else(|| (StatusCode::UNAUTHORIZED, "User not found").into_response())?;
 
-        Ok(AuthUser { user })
+        let workspace_id = resolve_workspace(state, &user, headers).await?;
+        crate::services::i18n::use_user_locale(&state.db, user.id).await;
+
+        Ok(AuthUser { user, workspace_id })
+    }
+}
+
+/// A user of a client that only speaks HTTP Basic authentication, such as a WebDAV
+/// mount in Finder or Explorer: their username or email with an API token as the
+/// password. Account passwords are not accepted, so logins keep their second factor.
+/// Bearer tokens are accepted as for [`AuthUser`].
+pub struct BasicAuthUser(pub AuthUser);
+
+impl FromRequestParts<Arc<AppState>> for BasicAuthUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        if extract_token_from_headers(&parts.headers).is_some() {
+            return AuthUser::from_request_parts(parts, state).await.map(BasicAuthUser);
+        }
+
+        let (username, token) = basic_credentials(&parts.headers)
+            .filter(|(_, token)| token.starts_with(API_TOKEN_PREFIX))
+            .ok_or_else(basic_challenge)?;
+        let path = parts
+            .extensions
+            .get::<OriginalUri>()
+            .map(|uri| uri.0.path().to_string())
+            .unwrap_or_else(|| parts.uri.path().to_string());
+        let user_id = authenticate_api_token(state, &token, &parts.method, &path)
+            .await
+            .map_err(|response| if response.status() == StatusCode::UNAUTHORIZED { basic_challenge() } else { response })?;
+
+        let user = state
+            .db
+            .get_user_by_id(user_id)
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())?
+            .filter(|user| user.username.eq_ignore_ascii_case(&username) || user.email.eq_ignore_ascii_case(&username))
+            .ok_or_else(basic_challenge)?;
+
+        Ok(BasicAuthUser(AuthUser { user, workspace_id: None }))
     }
 }
 
+/// Username and password of a `Basic` authorization header
+fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let encoded = headers.get(AUTHORIZATION)?.to_str().ok()?.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(Base64::decode_vec(encoded.trim()).ok()?).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.trim().to_string(), password.to_string()))
+}
+
+/// 401 asking the client for Basic credentials, which makes file managers prompt for them
+fn basic_challenge() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(WWW_AUTHENTICATE, "Basic realm=\"Readur\", charset=\"UTF-8\"")],
+        "Authentication required",
+    )
+        .into_response()
+}
+
+/// A paired scanner bridge and the user it pushes as, from a bridge token. Bridge
+/// tokens are accepted by nothing but the bridge's own routes.
+pub struct ScanBridgeAuth {
+    pub bridge: ScanBridge,
+    pub auth_user: AuthUser,
+}
+
+impl FromRequestParts<Arc<AppState>> for ScanBridgeAuth {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let token = extract_token_from_headers(&parts.headers)
+            .filter(|token| token.starts_with(scan_bridge_service::SCAN_BRIDGE_TOKEN_PREFIX))
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Missing scan bridge token").into_response())?;
+
+        let bridge = state
+            .db
+            .get_scan_bridge_by_token_hash(&scan_bridge_service::hash_secret(&token))
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())?
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid or disabled scan bridge token").into_response())?;
+
+        let user = state
+            .db
+            .get_user_by_id(bridge.user_id)
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())?
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "User not found").into_response())?;
+
+        let client = ClientInfo::from_request_parts(parts, state).await.unwrap_or_else(|never| match never {});
+        if let Err(e) = state.db.touch_scan_bridge(bridge.id, client.ip_address.as_deref()).await {
+            tracing::warn!("Failed to record use of scan bridge {}: {}", bridge.id, e);
+        }
+
+        Ok(ScanBridgeAuth { bridge, auth_user: AuthUser { user, workspace_id: None } })
+    }
+}
+
+/// The workspace a request works in, if multi-tenant mode is on and the request names one
+async fn resolve_workspace(state: &AppState, user: &User, headers: &HeaderMap) -> Result<Option<Uuid>, Response> {
+    if !workspace_service::enabled() {
+        return Ok(None);
+    }
+    let Some(value) = headers.get(workspace_service::WORKSPACE_HEADER) else {
+        return Ok(None);
+    };
+    let workspace_id = value
+        .to_str()
+        .ok()
+        .and_then(|value| Uuid::parse_str(value.trim()).ok())
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid workspace ID").into_response())?;
+
+    let allowed = if user.role == UserRole::Admin {
+        state.db.get_workspace(workspace_id).await.map(|workspace| workspace.is_some())
+    } else {
+        state.db.get_workspace_role(workspace_id, user.id).await.map(|role| role.is_some())
+    }
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())?;
+
+    if !allowed {
+        return Err((StatusCode::FORBIDDEN, "Not a member of this workspace").into_response());
+    }
+    Ok(Some(workspace_id))
+}
+
+/// The user a JWT was issued to and its session, if the token is valid and its session
+/// has been neither revoked nor expired
+pub(crate) async fn authenticate_jwt(state: &AppState, token: &str) -> Result<(Uuid, Option<Uuid>), Response> {
+    let claims = authenticate_session(state, token).await?;
+    Ok((claims.claims.sub, claims.sid))
+}
+
+/// The claims of a valid JWT whose session is active. Impersonation tokens also need
+/// the admin who asked for them to still be one.
+async fn authenticate_session(state: &AppState, token: &str) -> Result<SessionClaims, Response> {
+    let claims = decode_session_claims(token, &state.config.jwt_secret)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token").into_response())?;
+
+    if let Some(impersonator) = &claims.imp {
+        let still_admin = state
+            .db
+            .get_user_by_id(impersonator.id)
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())?
+            .is_some_and(|admin| admin.role == UserRole::Admin);
+        // Impersonation tokens are always bound to a session
+        if !still_admin || claims.sid.is_none() {
+            return Err((StatusCode::UNAUTHORIZED, "Impersonation has ended").into_response());
+        }
+    }
+
+    if let Some(session_id) = claims.sid {
+        let session = state
+            .db
+            .get_active_session(session_id)
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())?
+            .filter(|session| session.user_id == claims.claims.sub)
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Session has ended").into_response())?;
+
+        if let Err(e) = state.db.touch_user_session(session.id).await {
+            tracing::warn!("Failed to record use of session {}: {}", session.id, e);
+        }
+    }
+
+    Ok(claims)
+}
+
+/// The user an API token belongs to, if the token exists, has not expired and its scope
+/// covers the request
+async fn authenticate_api_token(
+    state: &AppState,
+    token: &str,
+    method: &axum::http::Method,
+    path: &str,
+) -> Result<Uuid, Response> {
+    let api_token = state
+        .db
+        .get_api_token_by_hash(&hash_api_token(token))
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())?
+        .filter(|api_token| !api_token.is_expired(Utc::now()))
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid token").into_response())?;
+
+    if !api_token.scope.allows(method, path) {
+        return Err((StatusCode::FORBIDDEN, "API token scope does not allow this request").into_response());
+    }
+
+    if let Err(e) = state.db.touch_api_token(api_token.id).await {
+        tracing::warn!("Failed to record use of API token {}: {}", api_token.id, e);
+    }
+
+    Ok(api_token.user_id)
+}
+
+/// A new personal access token and the prefix shown in token lists
+pub fn generate_api_token() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let secret: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let token = format!("{}{}", API_TOKEN_PREFIX, secret);
+    let prefix = token[..API_TOKEN_DISPLAY_LENGTH].to_string();
+    (token, prefix)
+}
+
+pub fn hash_api_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
 pub fn create_jwt(user: &User, secret: &str) -> Result<String> {
     let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(24))
-        .expect("valid timestamp")
-        .timestamp();
-
-    let claims = Claims {
-        sub: user.id,
-        username: user.username.clone(),
-        exp: expiration as usize,
+        .checked_add_signed(Duration::hours(SESSION_HOURS))
+        .expect("valid timestamp");
+
+    encode_jwt(user, None, None, expiration.timestamp(), secret)
+}
+
+fn encode_jwt(
+    user: &User,
+    session_id: Option<Uuid>,
+    impersonator: Option<Impersonator>,
+    expiration: i64,
+    secret: &str,
+) -> Result<String> {
+    let claims = SessionClaims {
+        claims: Claims {
+            sub: user.id,
+            username: user.username.clone(),
+            exp: expiration as usize,
+        },
+        sid: session_id,
+        imp: impersonator,
     };
 
     let token = encode(
@@ -69,6 +343,113 @@ pub fn create_jwt(user: &User, secret: &str) -> Result<String> {
     Ok(token)
 }
 
+/// Log the user in: record a session and return a JWT bound to it, which stops working
+/// when the session is revoked. `method` is how the user authenticated, e.g. `password`.
+pub async fn start_session(state: &AppState, user: &User, method: &str, client: &ClientInfo) -> Result<String> {
+    let expires_at = Utc::now() + Duration::hours(SESSION_HOURS);
+    let session = state
+        .db
+        .create_user_session(
+            user.id,
+            method,
+            client.ip_address.as_deref(),
+            client.user_agent.as_deref(),
+            expires_at,
+        )
+        .await?;
+
+    encode_jwt(user, Some(session.id), None, expires_at.timestamp(), &state.config.jwt_secret)
+}
+
+/// Let an admin act as `user` for a while: record a session for the user and return a
+/// JWT bound to it that names the admin. Returns the token and its session.
+pub async fn start_impersonation(
+    state: &AppState,
+    admin: &User,
+    user: &User,
+    minutes: i64,
+    read_only: bool,
+    client: &ClientInfo,
+) -> Result<(String, UserSession)> {
+    let expires_at = Utc::now() + Duration::minutes(minutes);
+    let session = state
+        .db
+        .create_user_session(
+            user.id,
+            IMPERSONATION_METHOD,
+            client.ip_address.as_deref(),
+            client.user_agent.as_deref(),
+            expires_at,
+        )
+        .await?;
+
+    let impersonator = Impersonator { id: admin.id, username: admin.username.clone(), read_only };
+    let token = encode_jwt(user, Some(session.id), Some(impersonator), expires_at.timestamp(), &state.config.jwt_secret)?;
+    Ok((token, session))
+}
+
+/// The session of the JWT in the request headers, if it has one
+pub fn session_id_from_headers(headers: &HeaderMap, secret: &str) -> Option<Uuid> {
+    let token = extract_token_from_headers(headers)?;
+    decode_session_claims(&token, secret).ok()?.sid
+}
+
+/// The admin acting as the user, when the request carries an impersonation token
+pub fn impersonator_from_headers(headers: &HeaderMap, secret: &str) -> Option<Impersonator> {
+    let token = extract_token_from_headers(headers)?;
+    decode_session_claims(&token, secret).ok()?.imp
+}
+
+fn decode_session_claims(token: &str, secret: &str) -> Result<SessionClaims> {
+    let token_data = decode::<SessionClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )?;
+
+    Ok(token_data.claims)
+}
+
+/// A short-lived token standing in for a session between the password and the second
+/// factor of a login. It lacks the claims of a session token, so it cannot be used as one.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwoFactorChallengeClaims {
+    pub sub: Uuid,
+    /// `CHALLENGE_LOGIN` or `CHALLENGE_SETUP`
+    pub purpose: String,
+    pub exp: usize,
+}
+
+/// The account has a second factor; the token is exchanged for a session with a code
+pub const CHALLENGE_LOGIN: &str = "2fa_login";
+/// Two-factor login is required but not set up; the token may only enroll
+pub const CHALLENGE_SETUP: &str = "2fa_setup";
+
+pub fn create_two_factor_challenge(user_id: Uuid, purpose: &str, secret: &str) -> Result<String> {
+    let claims = TwoFactorChallengeClaims {
+        sub: user_id,
+        purpose: purpose.to_string(),
+        exp: (Utc::now() + Duration::minutes(5)).timestamp() as usize,
+    };
+
+    Ok(encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))?)
+}
+
+/// The user a challenge token was issued to, if it is valid and for `purpose`
+pub fn verify_two_factor_challenge(token: &str, purpose: &str, secret: &str) -> Result<Uuid> {
+    let claims = decode::<TwoFactorChallengeClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )?
+    .claims;
+
+    if claims.purpose != purpose {
+        return Err(anyhow::anyhow!("Challenge token is for {}, not {}", claims.purpose, purpose));
+    }
+    Ok(claims.sub)
+}
+
 pub fn verify_jwt(token: &str, secret: &str) -> Result<Claims> {
     let token_data = decode::<Claims>(
         token,
@@ -79,7 +460,7 @@ pub fn verify_jwt(token: &str, secret: &str) -> Result<Claims> {
     Ok(token_data.claims)
 }
 
-fn extract_token_from_headers(headers: &HeaderMap) -> Option<String> {
+pub(crate) fn extract_token_from_headers(headers: &HeaderMap) -> Option<String> {
     let auth_header = headers.get("authorization")?;
     let auth_str = auth_header.to_str().ok()?;
     
@@ -0,0 +1,47 @@
+//! Apply label rules to documents that already exist
+//!
+//! Usage: cargo run --bin apply_label_rules -- [--user-id <UUID>] [--label-id <UUID>]
+//!
+//! New rules only label documents ingested or processed afterwards; this backfills
+//! them. Labels are only added, so running it again is harmless.
+
+use anyhow::Result;
+use clap::Parser;
+use uuid::Uuid;
+
+use readur::{config::Config, db::Database, services::label_rule_service::LabelRuleService};
+
+#[derive(Parser)]
+#[command(name = "apply_label_rules")]
+#[command(about = "Apply the enabled label rules to existing documents")]
+struct Args {
+    /// Only apply this user's rules to their documents
+    #[arg(short, long)]
+    user_id: Option<Uuid>,
+
+    /// Only apply the rule of this label
+    #[arg(short, long)]
+    label_id: Option<Uuid>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn"));
+    tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .init();
+
+    let args = Args::parse();
+
+    let config = Config::from_env()?;
+    let db = Database::new(&config.database_url).await?;
+
+    let (documents_checked, labels_assigned) = LabelRuleService::new(db)
+        .backfill(args.user_id, args.label_id)
+        .await?;
+
+    println!("Checked {} documents, added {} labels", documents_checked, labels_assigned);
+
+    Ok(())
+}
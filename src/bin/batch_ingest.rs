@@ -67,9 +67,11 @@ async fn main() -> Result<()> {
     
     // Use storage factory to create file service with proper backend
     let storage_config = readur::storage::factory::storage_config_from_env(&config)?;
-    let file_service = std::sync::Arc::new(
-        FileService::from_config(storage_config, config.upload_path.clone()).await?
-    );
+    let mut file_service = FileService::from_config(storage_config, config.upload_path.clone()).await?;
+    if let Some(encryption) = readur::services::encryption::EncryptionService::from_env(db.clone())? {
+        file_service = file_service.with_encryption(std::sync::Arc::new(encryption));
+    }
+    let file_service = std::sync::Arc::new(file_service);
     
     // Initialize storage backend
     file_service.initialize_storage().await?;
@@ -0,0 +1,115 @@
+//! Bulk import for the initial migration of large archives
+//!
+//! Usage: cargo run --bin bulk_import -- /archive --user-id <UUID>
+//!
+//! Interrupted imports continue where they stopped when run again with the
+//! same directory (or `--manifest`).
+
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use readur::{
+    config::Config,
+    db::Database,
+    ingestion::bulk_import::{BulkImportOptions, BulkImporter},
+    ocr::queue::OcrQueueService,
+    services::file_service::FileService,
+};
+
+#[derive(Parser)]
+#[command(name = "bulk_import")]
+#[command(about = "Import a large directory tree with parallel hashing, batched inserts and a resumable manifest")]
+struct Args {
+    /// Directory to import files from
+    directory: PathBuf,
+
+    /// User ID to assign documents to
+    #[arg(short, long)]
+    user_id: Uuid,
+
+    /// Files hashed and stored concurrently
+    #[arg(short, long, default_value_t = 8)]
+    concurrency: usize,
+
+    /// Documents inserted per database statement (max 1000)
+    #[arg(short, long, default_value_t = 500)]
+    batch_size: usize,
+
+    /// Documents queued for OCR per minute after the import (0 = no limit)
+    #[arg(long, default_value_t = 600)]
+    ocr_rate: u32,
+
+    /// Generate thumbnails while queueing instead of on first view
+    #[arg(long)]
+    thumbnails: bool,
+
+    /// Manifest file (defaults to .readur-import-manifest.jsonl in the directory)
+    #[arg(short, long)]
+    manifest: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| {
+            tracing_subscriber::EnvFilter::new("info")
+                .add_directive("pdf_extract=error".parse().unwrap())
+        });
+    tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .init();
+
+    let args = Args::parse();
+
+    if !args.directory.is_dir() {
+        eprintln!("Error: Directory {} does not exist", args.directory.display());
+        std::process::exit(1);
+    }
+
+    let config = Config::from_env()?;
+    let db = Database::new(&config.database_url).await?;
+
+    let storage_config = readur::storage::factory::storage_config_from_env(&config)?;
+    let mut file_service = FileService::from_config(storage_config, config.upload_path.clone()).await?;
+    if let Some(encryption) = readur::services::encryption::EncryptionService::from_env(db.clone())? {
+        file_service = file_service.with_encryption(std::sync::Arc::new(encryption));
+    }
+    let file_service = std::sync::Arc::new(file_service);
+    file_service.initialize_storage().await?;
+
+    let queue_service = OcrQueueService::new(db.clone(), db.get_pool().clone(), 1, file_service.clone());
+    let options = BulkImportOptions {
+        concurrency: args.concurrency,
+        batch_size: args.batch_size,
+        ocr_rate_per_minute: args.ocr_rate,
+        generate_thumbnails: args.thumbnails,
+        manifest_path: args.manifest,
+    };
+    let importer = BulkImporter::new(db, queue_service, (*file_service).clone(), config, options);
+
+    println!("Starting bulk import from: {}", args.directory.display());
+    let summary = match importer.import_directory(&args.directory, args.user_id).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("Import stopped: {}", e);
+            eprintln!("Run the same command again to continue where it left off.");
+            std::process::exit(1);
+        }
+    };
+
+    println!("Bulk import finished:");
+    println!("  Files found:       {}", summary.found);
+    println!("  Already imported:  {}", summary.already_done);
+    println!("  Imported:          {}", summary.imported);
+    println!("  Duplicates:        {}", summary.duplicates);
+    println!("  Skipped (size):    {}", summary.skipped);
+    println!("  Failed:            {}", summary.failed);
+    println!("  Queued for OCR:    {}", summary.queued);
+    if summary.failed > 0 {
+        println!("Failed files are listed in the manifest and are retried on the next run.");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+//! Move stored document files into another storage backend
+//!
+//! Usage: cargo run --bin migrate_storage -- --target azure --admin admin
+//!
+//! Runs the same migration as `POST /api/storage/migrations`, so the server can
+//! keep serving documents meanwhile: each file is copied, verified by hash and only
+//! then switched to its new path, and the server reads files from whichever backend
+//! their path points at. An interrupted run is continued with `--resume`.
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use readur::{
+    config::Config,
+    db::Database,
+    models::{StorageBackendKind, StorageMigrationStatus, UserRole},
+    services::{encryption::EncryptionService, file_service::FileService, storage_migration_service::StorageMigrationService},
+    storage::factory,
+};
+
+#[derive(Parser)]
+#[command(name = "migrate_storage")]
+#[command(about = "Move stored document files into another storage backend")]
+struct Args {
+    /// Backend to move files into: local, s3 or azure
+    #[arg(short, long)]
+    target: Option<String>,
+
+    /// Admin the migration is recorded as started by
+    #[arg(long)]
+    admin: Option<String>,
+
+    /// Documents per batch (defaults to STORAGE_MIGRATION_BATCH_SIZE or 50)
+    #[arg(short, long)]
+    batch_size: Option<i32>,
+
+    /// Delete local source files once every document has reconciled
+    #[arg(long)]
+    delete_source: bool,
+
+    /// Continue the migration that is already running instead of starting one
+    #[arg(long)]
+    resume: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter("info")
+        .init();
+
+    let args = Args::parse();
+    let config = Config::from_env()?;
+    let db = Database::new(&config.database_url).await?;
+
+    let storage_config = factory::storage_config_from_env(&config)?;
+    let file_service = FileService::from_config(storage_config, config.upload_path.clone()).await?;
+    let read_backends = factory::create_read_backends(&config, file_service.storage_type()).await?;
+    let mut file_service = file_service
+        .with_read_backends(read_backends)
+        .with_blob_store(db.clone(), false);
+    if let Some(encryption) = EncryptionService::from_env(db.clone())? {
+        file_service = file_service.with_encryption(Arc::new(encryption));
+    }
+
+    let service = StorageMigrationService::new(db.clone(), file_service, config.clone());
+
+    let migration_id = match db.get_running_storage_migration().await? {
+        Some(running) if args.resume => running.id,
+        Some(running) => {
+            return Err(anyhow!(
+                "Storage migration {} to {} is already running; pass --resume to continue it",
+                running.id,
+                running.target_backend
+            ));
+        }
+        None if args.resume => return Err(anyhow!("No storage migration is running")),
+        None => {
+            let target = args
+                .target
+                .ok_or_else(|| anyhow!("--target is required to start a migration"))
+                .and_then(|target| StorageBackendKind::try_from(target).map_err(|e| anyhow!(e)))?;
+            let admin_name = args.admin.ok_or_else(|| anyhow!("--admin is required to start a migration"))?;
+            let admin = db
+                .get_user_by_username(&admin_name)
+                .await?
+                .filter(|user| user.role == UserRole::Admin)
+                .ok_or_else(|| anyhow!("{} is not an admin", admin_name))?;
+
+            service.target_backend(target).await?;
+            let migration = db
+                .create_storage_migration(
+                    target,
+                    StorageMigrationService::batch_size(args.batch_size),
+                    args.delete_source,
+                    admin.id,
+                )
+                .await?;
+            info!("Started storage migration {} to {}", migration.id, target);
+            migration.id
+        }
+    };
+
+    let migration = service.run(migration_id).await?;
+    match migration.status {
+        StorageMigrationStatus::Completed => {
+            info!(
+                "Storage migration {} completed: {} migrated, {} already in place, {} failed",
+                migration.id, migration.migrated_documents, migration.skipped_documents, migration.failed_documents
+            );
+            if let Some(report) = migration.report {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            Ok(())
+        }
+        status => {
+            error!("Storage migration {} ended {}: {}", migration.id, status, migration.error.unwrap_or_default());
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Search the text of every document for a regular expression and report the matches
+//!
+//! Usage: cargo run --bin ocr_regex_report -- 'AKIA[0-9A-Z]{16}' --format csv --output matches.csv
+//!
+//! Matches are written as they are found. Patterns Postgres can evaluate the same way
+//! are used to prefilter documents through the trigram index on document text.
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use readur::{
+    config::Config,
+    db::Database,
+    services::ocr_regex_report::{self, DocumentMatch, RegexReportOptions},
+};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Csv,
+    Jsonl,
+}
+
+#[derive(Parser)]
+#[command(name = "ocr_regex_report")]
+#[command(about = "Report matches of a regular expression across all document text")]
+struct Args {
+    /// Regular expression (Rust regex syntax)
+    pattern: String,
+
+    /// Match without regard to case
+    #[arg(short = 'i', long)]
+    ignore_case: bool,
+
+    /// Characters of context to show on each side of a match
+    #[arg(long, default_value_t = 60)]
+    context: usize,
+
+    /// Only search this user's documents
+    #[arg(short, long)]
+    user_id: Option<Uuid>,
+
+    /// Report at most this many matches per document (0 for all)
+    #[arg(long, default_value_t = 20)]
+    max_per_document: usize,
+
+    /// Read every document instead of prefiltering with the text index
+    #[arg(long)]
+    full_scan: bool,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Write the report to this file instead of standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn"));
+    tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_writer(io::stderr)
+        .init();
+
+    let args = Args::parse();
+    let options = RegexReportOptions {
+        pattern: args.pattern.clone(),
+        case_insensitive: args.ignore_case,
+        context_chars: args.context,
+        user_id: args.user_id,
+        max_matches_per_document: args.max_per_document,
+        full_scan: args.full_scan,
+    };
+    if let Err(e) = ocr_regex_report::build_regex(&options) {
+        eprintln!("Error: {}", e);
+        std::process::exit(2);
+    }
+
+    let config = Config::from_env()?;
+    let db = Database::new(&config.database_url).await?;
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    if let Format::Csv = args.format {
+        writeln!(out, "document_id,user_id,filename,field,offset,match,before,after")?;
+    }
+
+    let format = args.format;
+    let summary = ocr_regex_report::run(&db, &options, |m| write_match(&mut out, format, &m)).await?;
+    out.flush()?;
+
+    eprintln!(
+        "Scanned {} documents{}: {} matches in {} documents",
+        summary.documents_scanned,
+        if summary.index_assisted { " (index-assisted)" } else { "" },
+        summary.matches,
+        summary.documents_matched
+    );
+
+    Ok(())
+}
+
+fn write_match(out: &mut dyn Write, format: Format, m: &DocumentMatch) -> Result<()> {
+    let t = &m.text_match;
+    match format {
+        Format::Text => writeln!(
+            out,
+            "{} {} [{}@{}]: …{}[{}]{}…",
+            m.document_id, m.filename, t.field, t.offset, t.before, t.matched, t.after
+        )?,
+        Format::Csv => writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            m.document_id,
+            m.user_id,
+            csv_field(&m.filename),
+            t.field,
+            t.offset,
+            csv_field(&t.matched),
+            csv_field(&t.before),
+            csv_field(&t.after)
+        )?,
+        Format::Jsonl => writeln!(out, "{}", serde_json::to_string(m)?)?,
+    }
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
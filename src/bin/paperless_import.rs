@@ -0,0 +1,106 @@
+//! Import a Paperless-ngx export into Readur
+//!
+//! Usage: cargo run --bin paperless_import -- /paperless-export --user-id <UUID>
+//!
+//! The export is what `document_exporter` writes. Running the import again
+//! skips documents that are already present.
+
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use readur::{
+    config::Config,
+    db::Database,
+    ingestion::paperless_import::{PaperlessImportOptions, PaperlessImporter},
+    ocr::queue::OcrQueueService,
+    services::file_service::FileService,
+};
+
+#[derive(Parser)]
+#[command(name = "paperless_import")]
+#[command(about = "Import documents, tags, correspondents, document types and custom fields from a Paperless-ngx export")]
+struct Args {
+    /// Directory written by Paperless-ngx's document_exporter
+    export_dir: PathBuf,
+
+    /// User ID to assign documents and labels to
+    #[arg(short, long)]
+    user_id: Uuid,
+
+    /// Import the archived PDF/A version instead of the original when there is one
+    #[arg(long)]
+    prefer_archive: bool,
+
+    /// Queue documents for OCR instead of keeping Paperless' text
+    #[arg(long)]
+    reocr: bool,
+
+    /// Also label documents with their correspondent
+    #[arg(long)]
+    correspondent_labels: bool,
+
+    /// Also label documents with their document type
+    #[arg(long)]
+    document_type_labels: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| {
+            tracing_subscriber::EnvFilter::new("info")
+                .add_directive("pdf_extract=error".parse().unwrap())
+        });
+    tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .init();
+
+    let args = Args::parse();
+
+    if !args.export_dir.is_dir() {
+        eprintln!("Error: Directory {} does not exist", args.export_dir.display());
+        std::process::exit(1);
+    }
+
+    let config = Config::from_env()?;
+    let db = Database::new(&config.database_url).await?;
+
+    let storage_config = readur::storage::factory::storage_config_from_env(&config)?;
+    let mut file_service = FileService::from_config(storage_config, config.upload_path.clone()).await?;
+    if let Some(encryption) = readur::services::encryption::EncryptionService::from_env(db.clone())? {
+        file_service = file_service.with_encryption(std::sync::Arc::new(encryption));
+    }
+    let file_service = std::sync::Arc::new(file_service);
+    file_service.initialize_storage().await?;
+
+    let queue_service = OcrQueueService::new(db.clone(), db.get_pool().clone(), 1, file_service.clone());
+    let options = PaperlessImportOptions {
+        prefer_archive: args.prefer_archive,
+        reocr: args.reocr,
+        correspondent_labels: args.correspondent_labels,
+        document_type_labels: args.document_type_labels,
+    };
+    let importer = PaperlessImporter::new(db, queue_service, (*file_service).clone(), options);
+
+    println!("Importing Paperless-ngx export from: {}", args.export_dir.display());
+    let summary = match importer.import_export(&args.export_dir, args.user_id).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("Import stopped: {}", e);
+            eprintln!("Run the same command again to continue; imported documents are skipped.");
+            std::process::exit(1);
+        }
+    };
+
+    println!("Paperless import finished:");
+    println!("  Documents in export: {}", summary.documents);
+    println!("  Imported:            {}", summary.imported);
+    println!("  Already present:     {}", summary.already_present);
+    println!("  Missing files:       {}", summary.missing_files);
+    println!("  Failed:              {}", summary.failed);
+    println!("  Queued for OCR:      {}", summary.queued);
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Rotate the encryption keys of users and workspaces and re-encrypt their stored documents
+//!
+//! Usage: cargo run --bin rotate_encryption_keys -- [--user alice | --workspace acme] [--kms-key-ref vault:readur] [--reencrypt]
+//!
+//! Does for every user and workspace (or the one given) what `POST /api/encryption/keys/rotate`
+//! does for one, in a separate process so the server keeps serving documents:
+//! files sealed with older key versions stay readable until they are rewritten.
+//! `--reencrypt-only` skips the rotation, e.g. to encrypt the files stored before
+//! `ENCRYPTION_MASTER_KEY` was set.
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use readur::{
+    config::Config,
+    db::Database,
+    models::KeyOwner,
+    services::{encryption::{self, EncryptionService}, file_service::FileService},
+    storage::factory,
+};
+
+#[derive(Parser)]
+#[command(name = "rotate_encryption_keys")]
+#[command(about = "Rotate the encryption keys of users and workspaces and re-encrypt their stored documents")]
+struct Args {
+    /// Only this user's personal key; every user and workspace when neither is given
+    #[arg(short, long, conflicts_with = "workspace")]
+    user: Option<String>,
+
+    /// Only the key of the workspace with this slug
+    #[arg(short, long)]
+    workspace: Option<String>,
+
+    /// Customer-managed key that wraps the new versions, e.g. `vault:readur`; the master key when omitted
+    #[arg(long)]
+    kms_key_ref: Option<String>,
+
+    /// Re-encrypt stored documents with the new key versions
+    #[arg(long)]
+    reencrypt: bool,
+
+    /// Re-encrypt stored documents with the active key versions without rotating
+    #[arg(long, conflicts_with = "kms_key_ref")]
+    reencrypt_only: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter("info")
+        .init();
+
+    let args = Args::parse();
+    let config = Config::from_env()?;
+    let db = Database::new(&config.database_url).await?;
+
+    let encryption = Arc::new(
+        EncryptionService::from_env(db.clone())?
+            .ok_or_else(|| anyhow!("Encryption is not enabled; set ENCRYPTION_MASTER_KEY"))?,
+    );
+    encryption::install(encryption.clone());
+    encryption::kms::KmsKeyRef::parse(args.kms_key_ref.as_deref())?;
+
+    let storage_config = factory::storage_config_from_env(&config)?;
+    let file_service = FileService::from_config(storage_config, config.upload_path.clone()).await?;
+    let read_backends = factory::create_read_backends(&config, file_service.storage_type()).await?;
+    let file_service = file_service
+        .with_read_backends(read_backends)
+        .with_encryption(encryption.clone())
+        .with_blob_store(db.clone(), false);
+
+    // Key owners with a name for the log
+    let mut owners: Vec<(String, KeyOwner)> = Vec::new();
+    if args.workspace.is_none() {
+        let users = match &args.user {
+            Some(username) => vec![db
+                .get_user_by_username(username)
+                .await?
+                .ok_or_else(|| anyhow!("User {} not found", username))?],
+            None => db.get_all_users().await?,
+        };
+        owners.extend(users.into_iter().map(|user| (format!("user {}", user.username), KeyOwner::User(user.id))));
+    }
+    if args.user.is_none() {
+        let workspaces = db.get_workspaces().await?;
+        let workspaces: Vec<_> = match &args.workspace {
+            Some(slug) => vec![workspaces
+                .into_iter()
+                .find(|workspace| &workspace.slug == slug)
+                .ok_or_else(|| anyhow!("Workspace {} not found", slug))?],
+            None => workspaces,
+        };
+        owners.extend(
+            workspaces
+                .into_iter()
+                .map(|workspace| (format!("workspace {}", workspace.slug), KeyOwner::Workspace(workspace.id))),
+        );
+    }
+
+    let mut failed_owners = 0;
+    for (name, owner) in &owners {
+        if !args.reencrypt_only {
+            match encryption.rotate(*owner, args.kms_key_ref.as_deref(), None).await {
+                Ok(key) => info!("Rotated the encryption key of {} to version {}", name, key.version),
+                Err(e) => {
+                    error!("Failed to rotate the encryption key of {}: {}", name, e);
+                    failed_owners += 1;
+                    continue;
+                }
+            }
+        }
+
+        if args.reencrypt || args.reencrypt_only {
+            match encryption::reencrypt_owner_data(&db, &file_service, *owner).await {
+                Ok((rewritten, failed)) => {
+                    info!("Re-encrypted {} files and sources of {} ({} failed)", rewritten, name, failed);
+                    let detail = format!("{} files and sources re-encrypted, {} failed", rewritten, failed);
+                    let active_key_id = encryption.active_key(*owner).await.ok().map(|key| key.id);
+                    encryption.record_event(active_key_id, *owner, "reencrypted", None, Some(&detail)).await;
+                    if failed > 0 {
+                        failed_owners += 1;
+                    }
+                }
+                Err(e) => {
+                    error!("Re-encrypting files of {} failed: {}", name, e);
+                    failed_owners += 1;
+                }
+            }
+        }
+    }
+
+    if failed_owners > 0 {
+        return Err(anyhow!("{} of {} users and workspaces were not fully processed", failed_owners, owners.len()));
+    }
+
+    info!("Processed {} users and workspaces", owners.len());
+    Ok(())
+}
@@ -0,0 +1,269 @@
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::models::{ConsistencyIssue, ConsistencyIssueKind, SourceStatus};
+use crate::ocr::queue::OcrQueueService;
+use crate::scheduling::source_scheduler::SourceScheduler;
+use crate::services::consistency_service::{ConsistencyService, QUARANTINE_DIR};
+use crate::services::encryption::{self, EncryptionService};
+use crate::services::file_service::{deduplication_enabled, FileService};
+use crate::services::sync_progress_tracker::SyncProgressTracker;
+use crate::storage::factory;
+use crate::AppState;
+
+/// How often `sync-source` checks whether the sync has finished
+const SYNC_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Maintenance tasks run against the database and storage of a deployment, without
+/// the web server
+#[derive(Subcommand)]
+pub enum AdminCommand {
+    /// Reset a user's password to ADMIN_PASSWORD, or to a generated one
+    ResetPassword {
+        /// Username of the account
+        username: String,
+    },
+    /// Put failed OCR jobs that have attempts left back in the queue
+    RequeueFailedOcr,
+    /// Check that every document's file is in storage and every stored file belongs
+    /// to a document; exits with an error when an issue is found
+    VerifyStorage {
+        /// Also read every file back and compare it with the hash recorded at ingestion
+        #[arg(long)]
+        verify_hashes: bool,
+    },
+    /// Delete stored files that no document refers to, including those quarantined
+    /// by an earlier consistency repair
+    VacuumOrphans {
+        /// List the files without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Sync a source now and wait for the sync to finish
+    SyncSource {
+        /// Id of the source
+        source_id: Uuid,
+    },
+}
+
+pub async fn run_admin_command(command: AdminCommand, config: &Config) -> Result<()> {
+    let db = Database::new(&config.database_url).await?;
+
+    match command {
+        AdminCommand::ResetPassword { username } => super::reset_user_password(&db, &username).await,
+        AdminCommand::RequeueFailedOcr => requeue_failed_ocr(config, db).await,
+        AdminCommand::VerifyStorage { verify_hashes } => verify_storage(config, db, verify_hashes).await,
+        AdminCommand::VacuumOrphans { dry_run } => vacuum_orphans(config, db, dry_run).await,
+        AdminCommand::SyncSource { source_id } => sync_source(config, db, source_id).await,
+    }
+}
+
+/// The file service as the server sets it up: files written by this command are
+/// encrypted and deduplicated the same way, and files left on an earlier backend
+/// can still be found
+async fn open_file_service(config: &Config, db: &Database) -> Result<FileService> {
+    let storage_config = factory::storage_config_from_env(config)?;
+    let mut file_service = FileService::from_config(storage_config, config.upload_path.clone())
+        .await
+        .context("Failed to create file service with storage backend")?;
+
+    let read_backends = factory::create_read_backends(config, file_service.storage_type()).await?;
+    file_service = file_service.with_read_backends(read_backends);
+
+    if let Some(encryption) = EncryptionService::from_env(db.clone())? {
+        let encryption = Arc::new(encryption);
+        encryption::install(encryption.clone());
+        file_service = file_service.with_encryption(encryption);
+    }
+    Ok(file_service.with_blob_store(db.clone(), deduplication_enabled()))
+}
+
+async fn requeue_failed_ocr(config: &Config, db: Database) -> Result<()> {
+    let file_service = Arc::new(open_file_service(config, &db).await?);
+    let queue_service = OcrQueueService::new(db.clone(), db.get_pool().clone(), 1, file_service);
+
+    let requeued = queue_service.requeue_failed_items().await?;
+    println!("Requeued {} failed OCR jobs", requeued);
+    Ok(())
+}
+
+async fn verify_storage(config: &Config, db: Database, verify_hashes: bool) -> Result<()> {
+    let file_service = open_file_service(config, &db).await?;
+    let skips_files = file_service.is_s3_enabled();
+    let report = ConsistencyService::new(db, file_service).run(false, verify_hashes).await?;
+
+    println!(
+        "Checked {} documents, {} stored files and {} file hashes",
+        report.documents_checked, report.files_checked, report.hashes_checked
+    );
+    if skips_files {
+        println!("Stored files without a document are not looked for on S3 storage");
+    }
+    for issue in &report.issues {
+        println!("{}", describe_issue(issue));
+    }
+
+    if !report.issues.is_empty() {
+        anyhow::bail!("Found {} inconsistencies between the database and storage", report.issues.len());
+    }
+    println!("No inconsistencies found");
+    Ok(())
+}
+
+async fn vacuum_orphans(config: &Config, db: Database, dry_run: bool) -> Result<()> {
+    let file_service = open_file_service(config, &db).await?;
+    if file_service.is_s3_enabled() {
+        anyhow::bail!("Orphaned files can only be vacuumed on local storage");
+    }
+    let quarantine = file_service.get_subdirectory_path(QUARANTINE_DIR);
+    let report = ConsistencyService::new(db, file_service).run(false, false).await?;
+
+    let mut paths: Vec<std::path::PathBuf> = report
+        .issues
+        .iter()
+        .filter(|issue| issue.kind == ConsistencyIssueKind::OrphanedFile)
+        .filter_map(|issue| issue.path.as_ref().map(std::path::PathBuf::from))
+        .collect();
+    match tokio::fs::read_dir(&quarantine).await {
+        Ok(mut entries) => {
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_file() {
+                    paths.push(entry.path());
+                }
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let mut freed_bytes = 0;
+    let mut failed = 0;
+    for path in &paths {
+        let size = tokio::fs::metadata(path).await.map(|m| m.len()).unwrap_or(0);
+        if dry_run {
+            println!("Would delete {}", path.display());
+            freed_bytes += size;
+            continue;
+        }
+        match tokio::fs::remove_file(path).await {
+            Ok(()) => {
+                println!("Deleted {}", path.display());
+                freed_bytes += size;
+            }
+            Err(e) => {
+                eprintln!("Failed to delete {}: {}", path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    let verb = if dry_run { "Would free" } else { "Freed" };
+    println!("{} {} bytes in {} orphaned files", verb, freed_bytes, paths.len() - failed);
+    if failed > 0 {
+        anyhow::bail!("{} orphaned files could not be deleted", failed);
+    }
+    Ok(())
+}
+
+/// Run the sync through the scheduler so it is recorded like one started from the
+/// web UI, then wait for the source to leave the syncing state
+async fn sync_source(config: &Config, db: Database, source_id: Uuid) -> Result<()> {
+    let source = db
+        .get_source_by_id(source_id)
+        .await?
+        .ok_or_else(|| anyhow!("Source {} not found", source_id))?;
+
+    let file_service = Arc::new(open_file_service(config, &db).await?);
+    let queue_service = Arc::new(OcrQueueService::new(db.clone(), db.get_pool().clone(), 1, file_service.clone()));
+    let state = Arc::new(AppState {
+        db: db.clone(),
+        config: config.clone(),
+        file_service,
+        webdav_scheduler: None,
+        source_scheduler: None,
+        queue_service,
+        oidc_client: None,
+        sync_progress_tracker: Arc::new(SyncProgressTracker::new()),
+        user_watch_service: None,
+        webdav_metrics_collector: None,
+    });
+
+    println!("Syncing source {} ({})", source.name, source.source_type);
+    SourceScheduler::new(state)
+        .trigger_sync(source_id)
+        .await
+        .map_err(|e| anyhow!("Failed to start the sync: {}", e))?;
+
+    let source = loop {
+        tokio::time::sleep(SYNC_POLL_INTERVAL).await;
+        let source = db
+            .get_source_by_id(source_id)
+            .await?
+            .ok_or_else(|| anyhow!("Source {} was deleted during the sync", source_id))?;
+        if source.status != SourceStatus::Syncing {
+            break source;
+        }
+    };
+
+    if source.status == SourceStatus::Error {
+        anyhow::bail!(
+            "Sync failed: {}",
+            source.last_error.as_deref().unwrap_or("unknown error")
+        );
+    }
+    match db.list_source_sync_runs(source_id, 1, 0).await?.first() {
+        Some(run) => println!(
+            "Sync {}: {} added, {} updated, {} skipped, {} failed",
+            run.status, run.files_added, run.files_updated, run.files_skipped, run.files_failed
+        ),
+        None => println!("Sync finished"),
+    }
+    Ok(())
+}
+
+fn describe_issue(issue: &ConsistencyIssue) -> String {
+    let kind = match issue.kind {
+        ConsistencyIssueKind::MissingFile => "missing file",
+        ConsistencyIssueKind::OrphanedFile => "orphaned file",
+        ConsistencyIssueKind::DanglingGraphEdge => "dangling graph edge",
+        ConsistencyIssueKind::OrphanedQueueEntry => "orphaned queue entry",
+        ConsistencyIssueKind::HashMismatch => "hash mismatch",
+    };
+    let subject = issue
+        .path
+        .clone()
+        .or_else(|| issue.record_id.map(|id| id.to_string()))
+        .or_else(|| issue.document_id.map(|id| id.to_string()))
+        .unwrap_or_default();
+    format!("[{}] {}: {}", kind, subject, issue.detail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_issue_prefers_the_path() {
+        let document_id = Uuid::new_v4();
+        let mut issue = ConsistencyIssue {
+            kind: ConsistencyIssueKind::MissingFile,
+            document_id: Some(document_id),
+            record_id: None,
+            path: Some("./uploads/documents/a.pdf".to_string()),
+            detail: "Document file not found in storage".to_string(),
+            repaired: false,
+        };
+        assert_eq!(
+            describe_issue(&issue),
+            "[missing file] ./uploads/documents/a.pdf: Document file not found in storage"
+        );
+
+        issue.path = None;
+        assert!(describe_issue(&issue).contains(&document_id.to_string()));
+    }
+}
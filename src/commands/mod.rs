@@ -1,3 +1,5 @@
+pub mod admin;
 pub mod reset_admin;
 
-pub use reset_admin::reset_admin_password;
+pub use admin::{run_admin_command, AdminCommand};
+pub use reset_admin::{reset_admin_password, reset_user_password};
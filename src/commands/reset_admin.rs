@@ -20,17 +20,21 @@ use crate::utils::security::generate_secure_password;
 pub async fn reset_admin_password(db: &Database) -> Result<()> {
     // Get admin username from env var or use default
     let admin_username = env::var("ADMIN_USERNAME").unwrap_or_else(|_| "admin".to_string());
+    reset_user_password(db, &admin_username).await
+}
 
-    // Check if admin user exists
-    let admin_user = db
-        .get_user_by_username(&admin_username)
+/// Reset the password of any user, to ADMIN_PASSWORD or a newly generated password
+pub async fn reset_user_password(db: &Database, username: &str) -> Result<()> {
+    // Check if the user exists
+    let user = db
+        .get_user_by_username(username)
         .await
-        .context("Failed to query database for admin user")?;
+        .context("Failed to query database for user")?;
 
-    if admin_user.is_none() {
+    if user.is_none() {
         anyhow::bail!(
-            "Admin user '{}' not found. Please ensure the user exists before resetting password.",
-            admin_username
+            "User '{}' not found. Please ensure the user exists before resetting password.",
+            username
         );
     }
 
@@ -45,12 +49,12 @@ pub async fn reset_admin_password(db: &Database) -> Result<()> {
     };
 
     // Reset the password
-    db.reset_user_password(&admin_username, &new_password)
+    db.reset_user_password(username, &new_password)
         .await
-        .context("Failed to reset admin password")?;
+        .context("Failed to reset password")?;
 
     // Display success message with credentials
-    print_success_message(&admin_username, &new_password);
+    print_success_message(username, &new_password);
 
     Ok(())
 }
@@ -58,7 +62,7 @@ pub async fn reset_admin_password(db: &Database) -> Result<()> {
 fn print_success_message(username: &str, password: &str) {
     println!();
     println!("==============================================");
-    println!("  ADMIN PASSWORD RESET SUCCESSFUL");
+    println!("  PASSWORD RESET SUCCESSFUL");
     println!("==============================================");
     println!();
     println!("Username: {}", username);
@@ -552,6 +552,7 @@ impl Config {
                         file_extensions: vec![], // Will be configured separately for sources
                         auto_sync: false, // Not used for general storage
                         sync_interval_minutes: 0, // Not used for general storage
+                        notifications: None,
                     })
                 } else {
                     println!("❌ S3 enabled but missing required configuration (bucket_name, access_key_id, or secret_access_key)");
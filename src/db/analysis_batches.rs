@@ -0,0 +1,260 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    AnalysisBatch, AnalysisBatchFilter, AnalysisBatchStatus, AnalysisCostEstimate, BulkOperationError, UserRole,
+};
+
+const ANALYSIS_BATCH_COLUMNS: &str = "id, user_id, filter, concurrency, model, status, total_documents, \
+    processed_documents, succeeded_documents, failed_documents, errors, estimated_input_tokens, \
+    estimated_output_tokens, estimated_cost, error, cancelled_by, created_at, started_at, completed_at";
+
+impl Database {
+    /// Documents with text that match the filter, oldest first. Admins match everyone's
+    /// documents, other users only their own.
+    pub async fn find_analysis_batch_documents(
+        &self,
+        user_id: Uuid,
+        user_role: UserRole,
+        filter: &AnalysisBatchFilter,
+        limit: i64,
+    ) -> Result<Vec<Uuid>> {
+        let document_ids = sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT d.id FROM documents d
+               WHERE ($2 OR d.user_id = $1)
+                 AND COALESCE(NULLIF(d.ocr_text, ''), d.content, '') <> ''
+                 AND ($3::uuid IS NULL OR EXISTS (
+                     SELECT 1 FROM document_labels dl WHERE dl.document_id = d.id AND dl.label_id = $3))
+                 AND ($4::uuid IS NULL OR d.source_id = $4)
+                 AND (NOT $5 OR NOT EXISTS (SELECT 1 FROM document_nodes n WHERE n.document_id = d.id))
+               ORDER BY d.created_at, d.id
+               LIMIT $6"#
+        )
+        .bind(user_id)
+        .bind(user_role == UserRole::Admin)
+        .bind(filter.label_id)
+        .bind(filter.source_id)
+        .bind(filter.missing_graph_only)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(document_ids)
+    }
+
+    /// Characters of the documents' text that extraction would send, each document
+    /// counted up to `max_chars`
+    pub async fn count_analysis_text_chars(&self, document_ids: &[Uuid], max_chars: i32) -> Result<i64> {
+        let chars = sqlx::query_scalar::<_, Option<i64>>(
+            r#"SELECT SUM(LEAST(char_length(COALESCE(NULLIF(ocr_text, ''), content, '')), $2))::BIGINT
+               FROM documents WHERE id = ANY($1)"#
+        )
+        .bind(document_ids)
+        .bind(max_chars)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(chars.unwrap_or(0))
+    }
+
+    pub async fn create_analysis_batch(
+        &self,
+        user_id: Uuid,
+        filter: &AnalysisBatchFilter,
+        concurrency: i32,
+        document_ids: &[Uuid],
+        estimate: &AnalysisCostEstimate,
+    ) -> Result<AnalysisBatch> {
+        let query = format!(
+            r#"INSERT INTO analysis_batches
+                   (user_id, filter, document_ids, concurrency, model, total_documents,
+                    estimated_input_tokens, estimated_output_tokens, estimated_cost)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+               RETURNING {}"#,
+            ANALYSIS_BATCH_COLUMNS
+        );
+        let batch = sqlx::query_as::<_, AnalysisBatch>(&query)
+            .bind(user_id)
+            .bind(sqlx::types::Json(filter))
+            .bind(document_ids)
+            .bind(concurrency)
+            .bind(&estimate.model)
+            .bind(document_ids.len() as i32)
+            .bind(estimate.input_tokens)
+            .bind(estimate.output_tokens)
+            .bind(estimate.estimated_cost)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(batch)
+    }
+
+    pub async fn get_analysis_batch(&self, id: Uuid) -> Result<Option<AnalysisBatch>> {
+        let query = format!("SELECT {} FROM analysis_batches WHERE id = $1", ANALYSIS_BATCH_COLUMNS);
+        let batch = sqlx::query_as::<_, AnalysisBatch>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(batch)
+    }
+
+    /// The user's batches, newest first
+    pub async fn list_analysis_batches(&self, user_id: Uuid, limit: i64) -> Result<Vec<AnalysisBatch>> {
+        let query = format!(
+            "SELECT {} FROM analysis_batches WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2",
+            ANALYSIS_BATCH_COLUMNS
+        );
+        let batches = sqlx::query_as::<_, AnalysisBatch>(&query)
+            .bind(user_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(batches)
+    }
+
+    /// Batches that were queued or running when the server stopped, oldest first
+    /// Take over the queued or running batches no node online is working on. Nodes
+    /// claiming at the same time get different batches.
+    pub async fn claim_analysis_batches(&self, node_id: Uuid) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"UPDATE analysis_batches SET node_id = $1
+               WHERE id IN (
+                   SELECT id FROM analysis_batches
+                   WHERE status IN ('queued', 'running')
+                     AND (node_id IS NULL OR node_id NOT IN (SELECT id FROM live_worker_nodes))
+                   ORDER BY created_at
+                   FOR UPDATE SKIP LOCKED
+               )
+               RETURNING id"#
+        )
+        .bind(node_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// Claim a batch just created; false when another node took it first
+    pub async fn claim_analysis_batch(&self, id: Uuid, node_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("UPDATE analysis_batches SET node_id = $2 WHERE id = $1 AND node_id IS NULL")
+            .bind(id)
+            .bind(node_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Leave a batch to the next node claiming it, with its progress so far
+    pub async fn release_analysis_batch(&self, id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE analysis_batches SET node_id = NULL WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_analysis_batch_document_ids(&self, id: Uuid) -> Result<Vec<Uuid>> {
+        let document_ids = sqlx::query_scalar::<_, Vec<Uuid>>("SELECT document_ids FROM analysis_batches WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(document_ids.unwrap_or_default())
+    }
+
+    pub async fn get_analysis_batch_status(&self, id: Uuid) -> Result<Option<AnalysisBatchStatus>> {
+        let status = sqlx::query_scalar::<_, String>("SELECT status FROM analysis_batches WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        status.map(AnalysisBatchStatus::try_from).transpose().map_err(anyhow::Error::msg)
+    }
+
+    pub async fn start_analysis_batch(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE analysis_batches
+               SET status = 'running', started_at = COALESCE(started_at, NOW())
+               WHERE id = $1 AND status IN ('queued', 'running')"#
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Add a chunk's counts and failures to the batch's progress
+    pub async fn update_analysis_batch_progress(
+        &self,
+        id: Uuid,
+        succeeded: i32,
+        failed: i32,
+        errors: &[BulkOperationError],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE analysis_batches
+               SET processed_documents = processed_documents + $2 + $3,
+                   succeeded_documents = succeeded_documents + $2,
+                   failed_documents = failed_documents + $3,
+                   errors = errors || $4
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .bind(succeeded)
+        .bind(failed)
+        .bind(sqlx::types::Json(errors))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records how the batch ended, unless it was cancelled meanwhile
+    pub async fn finish_analysis_batch(
+        &self,
+        id: Uuid,
+        status: AnalysisBatchStatus,
+        error: Option<&str>,
+    ) -> Result<Option<AnalysisBatch>> {
+        let query = format!(
+            r#"UPDATE analysis_batches
+               SET status = $2, error = $3, completed_at = NOW()
+               WHERE id = $1 AND status IN ('queued', 'running')
+               RETURNING {}"#,
+            ANALYSIS_BATCH_COLUMNS
+        );
+        let batch = sqlx::query_as::<_, AnalysisBatch>(&query)
+            .bind(id)
+            .bind(status.to_string())
+            .bind(error)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(batch)
+    }
+
+    /// Stops a queued or running batch; the documents in flight still finish. None if
+    /// the batch had already ended.
+    pub async fn cancel_analysis_batch(&self, id: Uuid, cancelled_by: Uuid) -> Result<Option<AnalysisBatch>> {
+        let query = format!(
+            r#"UPDATE analysis_batches
+               SET status = 'cancelled', cancelled_by = $2, completed_at = NOW()
+               WHERE id = $1 AND status IN ('queued', 'running')
+               RETURNING {}"#,
+            ANALYSIS_BATCH_COLUMNS
+        );
+        let batch = sqlx::query_as::<_, AnalysisBatch>(&query)
+            .bind(id)
+            .bind(cancelled_by)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(batch)
+    }
+}
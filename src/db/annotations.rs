@@ -0,0 +1,203 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    AnnotationComment, CreateAnnotationRequest, DocumentAnnotation, UpdateAnnotationRequest,
+};
+
+const ANNOTATION_SELECT: &str = r#"
+    SELECT a.id, a.document_id, a.user_id, u.username, a.kind, a.page, a.x, a.y, a.width, a.height,
+           a.color, a.text, a.created_at, a.updated_at
+    FROM document_annotations a
+    LEFT JOIN users u ON u.id = a.user_id
+"#;
+
+const COMMENT_SELECT: &str = r#"
+    SELECT c.id, c.annotation_id, c.parent_id, c.user_id, u.username, c.body, c.created_at, c.updated_at
+    FROM annotation_comments c
+    LEFT JOIN users u ON u.id = c.user_id
+"#;
+
+impl Database {
+    /// Annotations of a document in page order, optionally of one page only
+    pub async fn get_document_annotations(&self, document_id: Uuid, page: Option<i32>) -> Result<Vec<DocumentAnnotation>> {
+        let annotations = sqlx::query_as::<_, DocumentAnnotation>(&format!(
+            "{} WHERE a.document_id = $1 AND ($2::INTEGER IS NULL OR a.page = $2) ORDER BY a.page, a.y, a.x, a.created_at",
+            ANNOTATION_SELECT
+        ))
+        .bind(document_id)
+        .bind(page)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(annotations)
+    }
+
+    pub async fn get_annotation(&self, document_id: Uuid, annotation_id: Uuid) -> Result<Option<DocumentAnnotation>> {
+        let annotation = sqlx::query_as::<_, DocumentAnnotation>(&format!(
+            "{} WHERE a.id = $1 AND a.document_id = $2",
+            ANNOTATION_SELECT
+        ))
+        .bind(annotation_id)
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(annotation)
+    }
+
+    pub async fn create_annotation(
+        &self,
+        document_id: Uuid,
+        user_id: Uuid,
+        request: &CreateAnnotationRequest,
+    ) -> Result<DocumentAnnotation> {
+        let id: Uuid = sqlx::query_scalar(
+            r#"INSERT INTO document_annotations (document_id, user_id, kind, page, x, y, width, height, color, text)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+               RETURNING id"#
+        )
+        .bind(document_id)
+        .bind(user_id)
+        .bind(request.kind)
+        .bind(request.page)
+        .bind(request.x)
+        .bind(request.y)
+        .bind(request.width)
+        .bind(request.height)
+        .bind(&request.color)
+        .bind(&request.text)
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.get_annotation(document_id, id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Annotation {} disappeared after creation", id))
+    }
+
+    pub async fn update_annotation(
+        &self,
+        document_id: Uuid,
+        annotation_id: Uuid,
+        request: &UpdateAnnotationRequest,
+    ) -> Result<Option<DocumentAnnotation>> {
+        let updated = sqlx::query(
+            r#"UPDATE document_annotations
+               SET x = COALESCE($3, x), y = COALESCE($4, y), width = COALESCE($5, width),
+                   height = COALESCE($6, height), color = COALESCE($7, color), text = COALESCE($8, text),
+                   updated_at = NOW()
+               WHERE id = $1 AND document_id = $2"#
+        )
+        .bind(annotation_id)
+        .bind(document_id)
+        .bind(request.x)
+        .bind(request.y)
+        .bind(request.width)
+        .bind(request.height)
+        .bind(&request.color)
+        .bind(&request.text)
+        .execute(&self.pool)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_annotation(document_id, annotation_id).await
+    }
+
+    /// Deletes the annotation with its comments. Returns false when it did not exist.
+    pub async fn delete_annotation(&self, document_id: Uuid, annotation_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM document_annotations WHERE id = $1 AND document_id = $2")
+            .bind(annotation_id)
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Comments on the given annotations, oldest first
+    pub async fn get_annotation_comments(&self, annotation_ids: &[Uuid]) -> Result<Vec<AnnotationComment>> {
+        if annotation_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let comments = sqlx::query_as::<_, AnnotationComment>(&format!(
+            "{} WHERE c.annotation_id = ANY($1) ORDER BY c.created_at",
+            COMMENT_SELECT
+        ))
+        .bind(annotation_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(comments)
+    }
+
+    pub async fn get_annotation_comment(&self, annotation_id: Uuid, comment_id: Uuid) -> Result<Option<AnnotationComment>> {
+        let comment = sqlx::query_as::<_, AnnotationComment>(&format!(
+            "{} WHERE c.id = $1 AND c.annotation_id = $2",
+            COMMENT_SELECT
+        ))
+        .bind(comment_id)
+        .bind(annotation_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(comment)
+    }
+
+    pub async fn create_annotation_comment(
+        &self,
+        annotation_id: Uuid,
+        parent_id: Option<Uuid>,
+        user_id: Uuid,
+        body: &str,
+    ) -> Result<AnnotationComment> {
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO annotation_comments (annotation_id, parent_id, user_id, body) VALUES ($1, $2, $3, $4) RETURNING id"
+        )
+        .bind(annotation_id)
+        .bind(parent_id)
+        .bind(user_id)
+        .bind(body.trim())
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.get_annotation_comment(annotation_id, id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Comment {} disappeared after creation", id))
+    }
+
+    pub async fn update_annotation_comment(
+        &self,
+        annotation_id: Uuid,
+        comment_id: Uuid,
+        body: &str,
+    ) -> Result<Option<AnnotationComment>> {
+        let updated = sqlx::query(
+            "UPDATE annotation_comments SET body = $3, updated_at = NOW() WHERE id = $1 AND annotation_id = $2"
+        )
+        .bind(comment_id)
+        .bind(annotation_id)
+        .bind(body.trim())
+        .execute(&self.pool)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_annotation_comment(annotation_id, comment_id).await
+    }
+
+    /// Deletes the comment with its replies. Returns false when it did not exist.
+    pub async fn delete_annotation_comment(&self, annotation_id: Uuid, comment_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM annotation_comments WHERE id = $1 AND annotation_id = $2")
+            .bind(comment_id)
+            .bind(annotation_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
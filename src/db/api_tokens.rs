@@ -0,0 +1,87 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{ApiToken, ApiTokenScope};
+
+const API_TOKEN_FIELDS: &str =
+    "id, user_id, name, token_prefix, scope, expires_at, last_used_at, created_at";
+
+impl Database {
+    pub async fn create_api_token(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        token_prefix: &str,
+        token_hash: &str,
+        scope: ApiTokenScope,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<ApiToken> {
+        let query = format!(
+            r#"INSERT INTO api_tokens (user_id, name, token_prefix, token_hash, scope, expires_at)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               RETURNING {}"#,
+            API_TOKEN_FIELDS
+        );
+        let token = sqlx::query_as::<_, ApiToken>(&query)
+            .bind(user_id)
+            .bind(name.trim())
+            .bind(token_prefix)
+            .bind(token_hash)
+            .bind(scope)
+            .bind(expires_at)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(token)
+    }
+
+    pub async fn get_api_tokens(&self, user_id: Uuid) -> Result<Vec<ApiToken>> {
+        let query = format!(
+            "SELECT {} FROM api_tokens WHERE user_id = $1 ORDER BY created_at DESC",
+            API_TOKEN_FIELDS
+        );
+        let tokens = sqlx::query_as::<_, ApiToken>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(tokens)
+    }
+
+    pub async fn get_api_token_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let query = format!("SELECT {} FROM api_tokens WHERE token_hash = $1", API_TOKEN_FIELDS);
+        let token = sqlx::query_as::<_, ApiToken>(&query)
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Records a use of the token, at most once a minute to keep busy scripts from
+    /// writing on every request
+    pub async fn touch_api_token(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE api_tokens SET last_used_at = NOW()
+               WHERE id = $1 AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')"#
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns false when the user has no such token
+    pub async fn delete_api_token(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
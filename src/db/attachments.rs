@@ -0,0 +1,43 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::DocumentAttachment;
+
+impl Database {
+    pub async fn create_document_attachment(
+        &self,
+        parent_document_id: Uuid,
+        child_document_id: Uuid,
+        filename: &str,
+        position: i32,
+    ) -> Result<DocumentAttachment> {
+        let attachment = sqlx::query_as::<_, DocumentAttachment>(
+            r#"INSERT INTO document_attachments (parent_document_id, child_document_id, filename, position)
+               VALUES ($1, $2, $3, $4)
+               RETURNING id, parent_document_id, child_document_id, filename, position, created_at"#
+        )
+        .bind(parent_document_id)
+        .bind(child_document_id)
+        .bind(filename)
+        .bind(position)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(attachment)
+    }
+
+    pub async fn get_document_attachments(&self, parent_document_id: Uuid) -> Result<Vec<DocumentAttachment>> {
+        let attachments = sqlx::query_as::<_, DocumentAttachment>(
+            r#"SELECT id, parent_document_id, child_document_id, filename, position, created_at
+               FROM document_attachments
+               WHERE parent_document_id = $1
+               ORDER BY position"#
+        )
+        .bind(parent_document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(attachments)
+    }
+}
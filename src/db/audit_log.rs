@@ -0,0 +1,133 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{AuditLogEntry, AuditLogQuery};
+use crate::utils::pagination::PageRequest;
+
+/// An action to add to the audit log
+#[derive(Debug, Clone, Default)]
+pub struct NewAuditLogEntry {
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub action: String,
+    pub resource_type: String,
+    pub resource_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// The admin who took the action while impersonating the user
+    pub impersonator_id: Option<Uuid>,
+    pub impersonator_username: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub details: Option<serde_json::Value>,
+}
+
+fn push_audit_filters(builder: &mut QueryBuilder<'_, Postgres>, query: &AuditLogQuery) {
+    builder.push(" WHERE TRUE");
+    if let Some(user_id) = query.user_id {
+        builder.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(action) = &query.action {
+        if action.ends_with('.') {
+            builder.push(" AND action LIKE ").push_bind(format!("{}%", action));
+        } else {
+            builder.push(" AND action = ").push_bind(action.clone());
+        }
+    }
+    if let Some(resource_type) = &query.resource_type {
+        builder.push(" AND resource_type = ").push_bind(resource_type.clone());
+    }
+    if let Some(resource_id) = query.resource_id {
+        builder.push(" AND resource_id = ").push_bind(resource_id);
+    }
+    if let Some(ip_address) = &query.ip_address {
+        builder.push(" AND ip_address = ").push_bind(ip_address.clone());
+    }
+    match query.impersonated {
+        Some(true) => {
+            builder.push(" AND impersonator_id IS NOT NULL");
+        }
+        Some(false) => {
+            builder.push(" AND impersonator_id IS NULL");
+        }
+        None => {}
+    }
+    if let Some(impersonator_id) = query.impersonator_id {
+        builder.push(" AND impersonator_id = ").push_bind(impersonator_id);
+    }
+    if let Some(from) = query.from {
+        builder.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        builder.push(" AND created_at < ").push_bind(to);
+    }
+}
+
+/// Write an entry with a bare pool, for services that are not given a [`Database`]
+pub async fn insert_audit_log_entry(pool: &PgPool, entry: &NewAuditLogEntry) -> Result<()> {
+    sqlx::query(
+        r#"INSERT INTO audit_log (
+               user_id, username, action, resource_type, resource_id,
+               ip_address, user_agent, before, after, details,
+               impersonator_id, impersonator_username
+           )
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"#
+    )
+    .bind(entry.user_id)
+    .bind(&entry.username)
+    .bind(&entry.action)
+    .bind(&entry.resource_type)
+    .bind(entry.resource_id)
+    .bind(&entry.ip_address)
+    .bind(&entry.user_agent)
+    .bind(&entry.before)
+    .bind(&entry.after)
+    .bind(&entry.details)
+    .bind(entry.impersonator_id)
+    .bind(&entry.impersonator_username)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+impl Database {
+    pub async fn create_audit_log_entry(&self, entry: &NewAuditLogEntry) -> Result<()> {
+        insert_audit_log_entry(&self.pool, entry).await
+    }
+
+    /// A page of the entries matching the query's filters, newest first, and how many
+    /// match in total when the page asks for it. Fetches `page.fetch_limit()` rows,
+    /// starting after the page's cursor or offset.
+    pub async fn get_audit_log(
+        &self,
+        query: &AuditLogQuery,
+        page: &PageRequest<DateTime<Utc>>,
+    ) -> Result<(Vec<AuditLogEntry>, Option<i64>)> {
+        let total = if page.include_total {
+            let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM audit_log");
+            push_audit_filters(&mut count, query);
+            Some(count.build_query_scalar().fetch_one(&self.pool).await?)
+        } else {
+            None
+        };
+
+        let mut select = QueryBuilder::<Postgres>::new(
+            "SELECT id, user_id, username, action, resource_type, resource_id, ip_address, user_agent, \
+             impersonator_id, impersonator_username, before, after, details, created_at FROM audit_log"
+        );
+        push_audit_filters(&mut select, query);
+        if let Some(after) = &page.after {
+            select.push(" AND (created_at, id) < (").push_bind(after.key);
+            select.push(", ").push_bind(after.id).push(")");
+        }
+        select.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(page.fetch_limit());
+        select.push(" OFFSET ").push_bind(page.offset);
+        let entries = select.build_query_as::<AuditLogEntry>().fetch_all(&self.pool).await?;
+
+        Ok((entries, total))
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{Backup, BackupKind, BackupStatus};
+
+const BACKUP_COLUMNS: &str = "id, export_id, destination, kind, changed_since, status, triggered_by, \
+    remote_path, archive_size, total_documents, failed_documents, error, created_at, completed_at, pruned_at";
+
+impl Database {
+    /// Record a new running backup. None when another backup is still running.
+    pub async fn create_backup(
+        &self,
+        destination: &str,
+        kind: BackupKind,
+        changed_since: Option<DateTime<Utc>>,
+        triggered_by: Option<Uuid>,
+    ) -> Result<Option<Backup>> {
+        let query = format!(
+            r#"INSERT INTO backups (destination, kind, changed_since, triggered_by)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT DO NOTHING
+               RETURNING {}"#,
+            BACKUP_COLUMNS
+        );
+        let backup = sqlx::query_as::<_, Backup>(&query)
+            .bind(destination)
+            .bind(kind.to_string())
+            .bind(changed_since)
+            .bind(triggered_by)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(backup)
+    }
+
+    /// Backups, newest first
+    pub async fn list_backups(&self, limit: i64) -> Result<Vec<Backup>> {
+        let query = format!("SELECT {} FROM backups ORDER BY created_at DESC LIMIT $1", BACKUP_COLUMNS);
+        let backups = sqlx::query_as::<_, Backup>(&query)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(backups)
+    }
+
+    /// The most recent backup with the status and kind, if any
+    pub async fn get_latest_backup(
+        &self,
+        status: Option<BackupStatus>,
+        kind: Option<BackupKind>,
+    ) -> Result<Option<Backup>> {
+        let query = format!(
+            r#"SELECT {} FROM backups
+               WHERE ($1::text IS NULL OR status = $1) AND ($2::text IS NULL OR kind = $2)
+               ORDER BY created_at DESC LIMIT 1"#,
+            BACKUP_COLUMNS
+        );
+        let backup = sqlx::query_as::<_, Backup>(&query)
+            .bind(status.map(|status| status.to_string()))
+            .bind(kind.map(|kind| kind.to_string()))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(backup)
+    }
+
+    /// Backups uploaded after `since`
+    pub async fn count_completed_backups_since(&self, since: DateTime<Utc>) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM backups WHERE status = 'completed' AND created_at > $1"
+        )
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    pub async fn set_backup_export(&self, id: Uuid, export_id: Uuid, total_documents: i32) -> Result<()> {
+        sqlx::query("UPDATE backups SET export_id = $2, total_documents = $3 WHERE id = $1")
+            .bind(id)
+            .bind(export_id)
+            .bind(total_documents)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn complete_backup(
+        &self,
+        id: Uuid,
+        remote_path: &str,
+        archive_size: i64,
+        failed_documents: i32,
+    ) -> Result<Backup> {
+        let query = format!(
+            r#"UPDATE backups
+               SET status = 'completed', remote_path = $2, archive_size = $3, failed_documents = $4,
+                   completed_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            BACKUP_COLUMNS
+        );
+        let backup = sqlx::query_as::<_, Backup>(&query)
+            .bind(id)
+            .bind(remote_path)
+            .bind(archive_size)
+            .bind(failed_documents)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(backup)
+    }
+
+    pub async fn fail_backup(&self, id: Uuid, error: &str) -> Result<Backup> {
+        let query = format!(
+            r#"UPDATE backups SET status = 'failed', error = $2, completed_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            BACKUP_COLUMNS
+        );
+        let backup = sqlx::query_as::<_, Backup>(&query)
+            .bind(id)
+            .bind(error)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(backup)
+    }
+
+    /// Mark the backups that were running when the server stopped as failed. Returns
+    /// the exports they were writing.
+    pub async fn fail_interrupted_backups(&self) -> Result<Vec<Uuid>> {
+        let export_ids = sqlx::query_scalar::<_, Option<Uuid>>(
+            r#"UPDATE backups SET status = 'failed', error = 'Interrupted by a restart', completed_at = NOW()
+               WHERE status = 'running'
+               RETURNING export_id"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(export_ids.into_iter().flatten().collect())
+    }
+
+    /// Uploaded backups older than the `keep_full`-th newest uploaded full backup,
+    /// oldest first. Incremental backups are kept as long as the full backup they build on.
+    pub async fn get_backups_to_prune(&self, keep_full: i64) -> Result<Vec<Backup>> {
+        let query = format!(
+            r#"SELECT {} FROM backups
+               WHERE status = 'completed'
+                 AND created_at < (
+                     SELECT created_at FROM backups
+                     WHERE status = 'completed' AND kind = 'full'
+                     ORDER BY created_at DESC
+                     LIMIT 1 OFFSET $1 - 1
+                 )
+               ORDER BY created_at"#,
+            BACKUP_COLUMNS
+        );
+        let backups = sqlx::query_as::<_, Backup>(&query)
+            .bind(keep_full)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(backups)
+    }
+
+    pub async fn mark_backup_pruned(&self, id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE backups SET status = 'pruned', pruned_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The oldest admin, who owns the exports that backups are written by
+    pub async fn get_backup_owner_id(&self) -> Result<Option<Uuid>> {
+        let user_id = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM users WHERE role = 'admin' ORDER BY created_at LIMIT 1"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user_id)
+    }
+}
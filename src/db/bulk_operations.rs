@@ -0,0 +1,198 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{BulkOperation, BulkOperationError, BulkOperationKind, BulkOperationParameters, BulkOperationStatus};
+
+const BULK_OPERATION_COLUMNS: &str = "id, user_id, operation, parameters, status, total_documents, \
+    processed_documents, succeeded_documents, failed_documents, errors, merged_document_id, error, \
+    created_at, started_at, completed_at";
+
+impl Database {
+    pub async fn create_bulk_operation(
+        &self,
+        user_id: Uuid,
+        operation: BulkOperationKind,
+        parameters: &BulkOperationParameters,
+        document_ids: &[Uuid],
+    ) -> Result<BulkOperation> {
+        let query = format!(
+            r#"INSERT INTO bulk_operations (user_id, operation, parameters, document_ids, total_documents)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING {}"#,
+            BULK_OPERATION_COLUMNS
+        );
+        let operation = sqlx::query_as::<_, BulkOperation>(&query)
+            .bind(user_id)
+            .bind(operation.to_string())
+            .bind(sqlx::types::Json(parameters))
+            .bind(document_ids)
+            .bind(document_ids.len() as i32)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(operation)
+    }
+
+    pub async fn get_bulk_operation(&self, id: Uuid) -> Result<Option<BulkOperation>> {
+        let query = format!("SELECT {} FROM bulk_operations WHERE id = $1", BULK_OPERATION_COLUMNS);
+        let operation = sqlx::query_as::<_, BulkOperation>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(operation)
+    }
+
+    /// The user's operations, newest first
+    pub async fn list_bulk_operations(&self, user_id: Uuid, limit: i64) -> Result<Vec<BulkOperation>> {
+        let query = format!(
+            "SELECT {} FROM bulk_operations WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2",
+            BULK_OPERATION_COLUMNS
+        );
+        let operations = sqlx::query_as::<_, BulkOperation>(&query)
+            .bind(user_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(operations)
+    }
+
+    /// Operations that were queued or running when the server stopped, oldest first
+    pub async fn get_unfinished_bulk_operations(&self) -> Result<Vec<BulkOperation>> {
+        let query = format!(
+            "SELECT {} FROM bulk_operations WHERE status IN ('queued', 'running') ORDER BY created_at",
+            BULK_OPERATION_COLUMNS
+        );
+        let operations = sqlx::query_as::<_, BulkOperation>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(operations)
+    }
+
+    pub async fn get_bulk_operation_document_ids(&self, id: Uuid) -> Result<Vec<Uuid>> {
+        let document_ids = sqlx::query_scalar::<_, Vec<Uuid>>("SELECT document_ids FROM bulk_operations WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(document_ids.unwrap_or_default())
+    }
+
+    pub async fn start_bulk_operation(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE bulk_operations SET status = 'running', started_at = COALESCE(started_at, NOW()) WHERE id = $1"
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Add a batch's counts and failures to the operation's progress
+    pub async fn update_bulk_operation_progress(
+        &self,
+        id: Uuid,
+        succeeded: i32,
+        failed: i32,
+        errors: &[BulkOperationError],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE bulk_operations
+               SET processed_documents = processed_documents + $2 + $3,
+                   succeeded_documents = succeeded_documents + $2,
+                   failed_documents = failed_documents + $3,
+                   errors = errors || $4
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .bind(succeeded)
+        .bind(failed)
+        .bind(sqlx::types::Json(errors))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn finish_bulk_operation(
+        &self,
+        id: Uuid,
+        status: BulkOperationStatus,
+        merged_document_id: Option<Uuid>,
+        error: Option<&str>,
+    ) -> Result<BulkOperation> {
+        let query = format!(
+            r#"UPDATE bulk_operations
+               SET status = $2, merged_document_id = $3, error = $4, completed_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            BULK_OPERATION_COLUMNS
+        );
+        let operation = sqlx::query_as::<_, BulkOperation>(&query)
+            .bind(id)
+            .bind(status.to_string())
+            .bind(merged_document_id)
+            .bind(error)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(operation)
+    }
+
+    /// How many of the labels are the user's own or system labels
+    pub async fn count_usable_labels(&self, user_id: Uuid, label_ids: &[Uuid]) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM labels WHERE id = ANY($1) AND (user_id = $2 OR is_system = TRUE)"
+        )
+        .bind(label_ids)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Give a document to another user. Its labels, correspondent and document type
+    /// belong to the previous owner, so the document loses them, except system labels.
+    pub async fn transfer_document_owner(&self, document_id: Uuid, owner_id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query("UPDATE documents SET user_id = $2, updated_at = NOW() WHERE id = $1 AND user_id <> $2")
+            .bind(document_id)
+            .bind(owner_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if updated == 0 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"DELETE FROM document_labels dl
+               USING labels l
+               WHERE dl.document_id = $1 AND l.id = dl.label_id AND l.is_system = FALSE AND l.user_id <> $2"#
+        )
+        .bind(document_id)
+        .bind(owner_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM document_correspondents WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM document_custom_fields WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+}
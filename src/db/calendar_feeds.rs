@@ -0,0 +1,123 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{CalendarEntry, CalendarFeed};
+
+const CALENDAR_FEED_SELECT: &str = r#"
+    SELECT user_id, lead_days, date_fields, last_accessed_at, created_at, updated_at
+    FROM calendar_feeds
+"#;
+
+/// Most events in one feed
+const MAX_CALENDAR_ENTRIES: i64 = 2000;
+
+impl Database {
+    pub async fn get_calendar_feed(&self, user_id: Uuid) -> Result<Option<CalendarFeed>> {
+        let feed = sqlx::query_as::<_, CalendarFeed>(&format!("{} WHERE user_id = $1", CALENDAR_FEED_SELECT))
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(feed)
+    }
+
+    /// The feed of a token, noting that it was fetched
+    pub async fn open_calendar_feed(&self, token_hash: &str) -> Result<Option<CalendarFeed>> {
+        let feed = sqlx::query_as::<_, CalendarFeed>(
+            r#"UPDATE calendar_feeds SET last_accessed_at = NOW()
+               WHERE token_hash = $1
+               RETURNING user_id, lead_days, date_fields, last_accessed_at, created_at, updated_at"#
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(feed)
+    }
+
+    /// Create the user's feed, or give it a new token so the old URL stops working.
+    /// Lead times and date fields of an existing feed are kept.
+    pub async fn set_calendar_feed_token(&self, user_id: Uuid, token_hash: &str) -> Result<CalendarFeed> {
+        let feed = sqlx::query_as::<_, CalendarFeed>(
+            r#"INSERT INTO calendar_feeds (user_id, token_hash)
+               VALUES ($1, $2)
+               ON CONFLICT (user_id) DO UPDATE SET token_hash = EXCLUDED.token_hash, updated_at = NOW()
+               RETURNING user_id, lead_days, date_fields, last_accessed_at, created_at, updated_at"#
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(feed)
+    }
+
+    pub async fn update_calendar_feed(
+        &self,
+        user_id: Uuid,
+        lead_days: Option<&[i32]>,
+        date_fields: Option<&[String]>,
+    ) -> Result<Option<CalendarFeed>> {
+        let feed = sqlx::query_as::<_, CalendarFeed>(
+            r#"UPDATE calendar_feeds
+               SET lead_days = COALESCE($2, lead_days),
+                   date_fields = COALESCE($3, date_fields),
+                   updated_at = NOW()
+               WHERE user_id = $1
+               RETURNING user_id, lead_days, date_fields, last_accessed_at, created_at, updated_at"#
+        )
+        .bind(user_id)
+        .bind(lead_days)
+        .bind(date_fields)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(feed)
+    }
+
+    pub async fn delete_calendar_feed(&self, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM calendar_feeds WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Dates in the given custom fields of the user's documents, from `since` on, in
+    /// date order. Values that are not YYYY-MM-DD dates are skipped.
+    pub async fn get_calendar_entries(
+        &self,
+        user_id: Uuid,
+        date_fields: &[String],
+        since: NaiveDate,
+    ) -> Result<Vec<CalendarEntry>> {
+        let entries = sqlx::query_as::<_, CalendarEntry>(
+            r#"SELECT document_id, filename, document_type, field, date
+               FROM (
+                   SELECT d.id AS document_id, d.original_filename AS filename, dt.name AS document_type,
+                          f.key AS field,
+                          CASE WHEN f.value ~ '^[0-9]{4}-[0-9]{2}-[0-9]{2}($|T)' THEN readur_try_date(left(f.value, 10)) END AS date
+                   FROM document_custom_fields dcf
+                   JOIN documents d ON d.id = dcf.document_id
+                   JOIN document_types dt ON dt.id = dcf.document_type_id
+                   CROSS JOIN LATERAL jsonb_each_text(dcf.fields) f
+                   WHERE d.user_id = $1
+                     AND f.key = ANY($2)
+               ) dates
+               WHERE date >= $3
+               ORDER BY date, filename
+               LIMIT $4"#
+        )
+        .bind(user_id)
+        .bind(date_fields)
+        .bind(since)
+        .bind(MAX_CALENDAR_ENTRIES)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+}
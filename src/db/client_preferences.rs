@@ -0,0 +1,77 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::ClientPreference;
+
+impl Database {
+    pub async fn list_client_preferences(&self, user_id: Uuid) -> Result<Vec<ClientPreference>> {
+        let preferences = sqlx::query_as::<_, ClientPreference>(
+            r#"SELECT namespace, schema_version, value, updated_at
+               FROM client_preferences WHERE user_id = $1 ORDER BY namespace"#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(preferences)
+    }
+
+    pub async fn get_client_preference(&self, user_id: Uuid, namespace: &str) -> Result<Option<ClientPreference>> {
+        let preference = sqlx::query_as::<_, ClientPreference>(
+            r#"SELECT namespace, schema_version, value, updated_at
+               FROM client_preferences WHERE user_id = $1 AND namespace = $2"#
+        )
+        .bind(user_id)
+        .bind(namespace)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(preference)
+    }
+
+    pub async fn count_client_preferences(&self, user_id: Uuid) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM client_preferences WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Store a namespace's value unless a higher schema version is stored; None when it is
+    pub async fn upsert_client_preference(
+        &self,
+        user_id: Uuid,
+        namespace: &str,
+        schema_version: i32,
+        value: &serde_json::Value,
+    ) -> Result<Option<ClientPreference>> {
+        let preference = sqlx::query_as::<_, ClientPreference>(
+            r#"INSERT INTO client_preferences (user_id, namespace, schema_version, value, updated_at)
+               VALUES ($1, $2, $3, $4, NOW())
+               ON CONFLICT (user_id, namespace) DO UPDATE
+               SET schema_version = EXCLUDED.schema_version, value = EXCLUDED.value, updated_at = NOW()
+               WHERE client_preferences.schema_version <= EXCLUDED.schema_version
+               RETURNING namespace, schema_version, value, updated_at"#
+        )
+        .bind(user_id)
+        .bind(namespace)
+        .bind(schema_version)
+        .bind(value)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(preference)
+    }
+
+    pub async fn delete_client_preference(&self, user_id: Uuid, namespace: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM client_preferences WHERE user_id = $1 AND namespace = $2")
+            .bind(user_id)
+            .bind(namespace)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::ClientSyncCursor;
+
+/// One entry of a user's change stream: a document created or changed at
+/// `changed_at`, or deleted then if `deleted` is set
+#[derive(Debug, Clone, FromRow)]
+pub struct ClientSyncChange {
+    pub changed_at: DateTime<Utc>,
+    pub document_id: Uuid,
+    pub deleted: bool,
+}
+
+impl Database {
+    /// Changes to a user's documents after `after`, oldest first. Changes newer than
+    /// `settle_seconds` are left for later, since a transaction that began earlier may
+    /// still commit a change that sorts before them.
+    pub async fn get_client_sync_changes(
+        &self,
+        user_id: Uuid,
+        after: Option<ClientSyncCursor>,
+        settle_seconds: i32,
+        limit: i64,
+    ) -> Result<Vec<ClientSyncChange>> {
+        let changes = sqlx::query_as::<_, ClientSyncChange>(
+            r#"SELECT changed_at, document_id, deleted FROM (
+                   SELECT updated_at AS changed_at, id AS document_id, FALSE AS deleted
+                   FROM documents WHERE user_id = $1
+                   UNION ALL
+                   SELECT deleted_at, document_id, TRUE
+                   FROM document_tombstones WHERE user_id = $1
+               ) changes
+               WHERE ($2::timestamptz IS NULL OR (changed_at, document_id) > ($2, $3))
+                 AND changed_at < NOW() - make_interval(secs => $4)
+               ORDER BY changed_at, document_id
+               LIMIT $5"#
+        )
+        .bind(user_id)
+        .bind(after.map(|c| c.changed_at))
+        .bind(after.map(|c| c.document_id))
+        .bind(settle_seconds)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(changes)
+    }
+
+    /// Label IDs assigned to each of the documents
+    pub async fn get_document_label_ids(&self, document_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<Uuid>>> {
+        let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+            "SELECT document_id, label_id FROM document_labels WHERE document_id = ANY($1) ORDER BY created_at"
+        )
+        .bind(document_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut labels: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for (document_id, label_id) in rows {
+            labels.entry(document_id).or_default().push(label_id);
+        }
+        Ok(labels)
+    }
+
+    /// How many of the labels the user may assign: their own and system labels
+    pub async fn count_assignable_labels(&self, user_id: Uuid, label_ids: &[Uuid]) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM labels WHERE id = ANY($1) AND (user_id = $2 OR is_system = TRUE)"
+        )
+        .bind(label_ids)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    pub async fn add_document_labels(&self, document_id: Uuid, label_ids: &[Uuid], assigned_by: Uuid) -> Result<()> {
+        if label_ids.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(
+            r#"INSERT INTO document_labels (document_id, label_id, assigned_by)
+               SELECT $1, label_id, $3 FROM UNNEST($2::uuid[]) AS label_id
+               ON CONFLICT DO NOTHING"#
+        )
+        .bind(document_id)
+        .bind(label_ids)
+        .bind(assigned_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_document_labels(&self, document_id: Uuid, label_ids: &[Uuid]) -> Result<()> {
+        if label_ids.is_empty() {
+            return Ok(());
+        }
+        sqlx::query("DELETE FROM document_labels WHERE document_id = $1 AND label_id = ANY($2)")
+            .bind(document_id)
+            .bind(label_ids)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+use anyhow::Result;
+use sqlx::{Postgres, QueryBuilder};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::documents::apply_shared_access_filter;
+use super::Database;
+use crate::models::{
+    depth_first_documents, Collection, CollectionDocument, CollectionListItem, CreateCollectionRequest,
+    SharePermission, UpdateCollectionRequest, UserRole,
+};
+
+const COLLECTION_SELECT: &str = r#"
+    SELECT c.id, c.user_id, c.parent_id, c.name, c.description, c.position,
+           (SELECT COUNT(*) FROM collection_documents cd WHERE cd.collection_id = c.id) AS document_count,
+           (SELECT COUNT(*) FROM collections child WHERE child.parent_id = c.id) AS child_count,
+           c.created_at, c.updated_at
+    FROM collections c
+"#;
+
+/// The collection and the collections enclosing it. `UNION` rather than `UNION ALL`
+/// so that a cycle ends the walk.
+const COLLECTION_ANCESTORS: &str = r#"
+    WITH RECURSIVE ancestors AS (
+        SELECT id, parent_id FROM collections WHERE id = $1
+        UNION
+        SELECT c.id, c.parent_id FROM collections c JOIN ancestors a ON c.id = a.parent_id
+    )
+"#;
+
+impl Database {
+    /// A new collection of `owner_id`, after the other collections of its parent
+    pub async fn create_collection(&self, owner_id: Uuid, request: &CreateCollectionRequest) -> Result<Collection> {
+        let id: Uuid = sqlx::query_scalar(
+            r#"INSERT INTO collections (user_id, parent_id, name, description, position)
+               VALUES ($1, $2, $3, NULLIF(btrim($4), ''),
+                       (SELECT COALESCE(MAX(position) + 1, 0) FROM collections
+                        WHERE user_id = $1 AND parent_id IS NOT DISTINCT FROM $2))
+               RETURNING id"#,
+        )
+        .bind(owner_id)
+        .bind(request.parent_id)
+        .bind(request.name.trim())
+        .bind(request.description.as_deref())
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.get_collection(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Collection {} disappeared after creation", id))
+    }
+
+    pub async fn get_collection(&self, id: Uuid) -> Result<Option<Collection>> {
+        let query = format!("{} WHERE c.id = $1", COLLECTION_SELECT);
+        let collection = sqlx::query_as::<_, Collection>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(collection)
+    }
+
+    /// The highest permission shares of the collection or a collection enclosing it
+    /// grant the user, directly or through a group
+    pub async fn get_collection_share_permission(&self, id: Uuid, user_id: Uuid) -> Result<Option<SharePermission>> {
+        let query = format!(
+            r#"{}
+               SELECT MAX(s.permission) FROM collection_shares s
+               WHERE s.collection_id IN (SELECT id FROM ancestors)
+                 AND (s.user_id = $2 OR s.group_id IN (SELECT group_id FROM user_group_members WHERE user_id = $2))"#,
+            COLLECTION_ANCESTORS
+        );
+        let permission = sqlx::query_scalar::<_, Option<SharePermission>>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(permission)
+    }
+
+    /// The user's collections and those shared with them, including the subcollections
+    /// of shared ones, top-level collections first
+    pub async fn get_collections_for_user(&self, user_id: Uuid) -> Result<Vec<CollectionListItem>> {
+        let collections = sqlx::query_as::<_, CollectionListItem>(
+            r#"WITH RECURSIVE granted AS (
+                   SELECT s.collection_id AS id, s.permission
+                   FROM collection_shares s
+                   WHERE s.user_id = $1
+                      OR s.group_id IN (SELECT group_id FROM user_group_members WHERE user_id = $1)
+                   UNION
+                   SELECT c.id, g.permission FROM collections c JOIN granted g ON c.parent_id = g.id
+               ),
+               access AS (
+                   SELECT id, 'delete'::share_permission AS permission FROM collections WHERE user_id = $1
+                   UNION ALL
+                   SELECT id, permission FROM granted
+               )
+               SELECT c.id, c.user_id, c.parent_id, c.name, c.description, c.position,
+                      (SELECT COUNT(*) FROM collection_documents cd WHERE cd.collection_id = c.id) AS document_count,
+                      (SELECT COUNT(*) FROM collections child WHERE child.parent_id = c.id) AS child_count,
+                      c.created_at, c.updated_at, u.username AS owner_username, a.permission
+               FROM (SELECT id, MAX(permission) AS permission FROM access GROUP BY id) a
+               JOIN collections c ON c.id = a.id
+               JOIN users u ON u.id = c.user_id
+               ORDER BY c.parent_id NULLS FIRST, c.position, LOWER(c.name)"#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(collections)
+    }
+
+    /// Subcollections in their order
+    pub async fn get_child_collections(&self, id: Uuid) -> Result<Vec<Collection>> {
+        let query = format!("{} WHERE c.parent_id = $1 ORDER BY c.position, LOWER(c.name)", COLLECTION_SELECT);
+        let collections = sqlx::query_as::<_, Collection>(&query)
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(collections)
+    }
+
+    pub async fn update_collection(&self, id: Uuid, request: &UpdateCollectionRequest) -> Result<Option<Collection>> {
+        let result = sqlx::query(
+            r#"UPDATE collections
+               SET name = COALESCE(btrim($2), name),
+                   description = CASE WHEN $3::text IS NULL THEN description ELSE NULLIF(btrim($3), '') END,
+                   updated_at = NOW()
+               WHERE id = $1"#,
+        )
+        .bind(id)
+        .bind(request.name.as_deref())
+        .bind(request.description.as_deref())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_collection(id).await
+    }
+
+    /// Move a collection after the other collections of `parent_id`, or to the top level.
+    /// Returns false when the new parent is the collection itself or inside it.
+    pub async fn move_collection(&self, id: Uuid, parent_id: Option<Uuid>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"WITH RECURSIVE ancestors AS (
+                   SELECT id, parent_id FROM collections WHERE id = $2
+                   UNION
+                   SELECT c.id, c.parent_id FROM collections c JOIN ancestors a ON c.id = a.parent_id
+               )
+               UPDATE collections c
+               SET parent_id = $2,
+                   position = (SELECT COALESCE(MAX(s.position) + 1, 0) FROM collections s
+                               WHERE s.user_id = c.user_id AND s.parent_id IS NOT DISTINCT FROM $2 AND s.id <> c.id),
+                   updated_at = NOW()
+               WHERE c.id = $1 AND NOT EXISTS (SELECT 1 FROM ancestors WHERE id = $1)"#,
+        )
+        .bind(id)
+        .bind(parent_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletes the collection with its subcollections; the documents stay
+    pub async fn delete_collection(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM collections WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The collection's documents the user can see, in the collection's order
+    pub async fn get_collection_documents(
+        &self,
+        collection_id: Uuid,
+        user_id: Uuid,
+        user_role: UserRole,
+    ) -> Result<Vec<CollectionDocument>> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT cd.document_id, d.original_filename AS filename, d.mime_type, d.file_size, cd.position, \
+             cd.added_by, cd.added_at \
+             FROM collection_documents cd \
+             JOIN documents d ON d.id = cd.document_id \
+             WHERE cd.collection_id = ",
+        );
+        query.push_bind(collection_id);
+        query.push(" AND d.id IN (SELECT id FROM documents WHERE 1=1");
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::View);
+        query.push(") ORDER BY cd.position, cd.added_at");
+
+        let documents = query.build_query_as::<CollectionDocument>().fetch_all(&self.pool).await?;
+        Ok(documents)
+    }
+
+    /// Append the documents the user can see to a collection, in the given order.
+    /// Returns how many were added; documents already in it keep their place.
+    pub async fn add_collection_documents(
+        &self,
+        collection_id: Uuid,
+        document_ids: &[Uuid],
+        user_id: Uuid,
+        user_role: UserRole,
+    ) -> Result<u64> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "INSERT INTO collection_documents (collection_id, document_id, position, added_by) \
+             SELECT c.id, r.id, \
+                    ((SELECT COALESCE(MAX(cd.position), -1) FROM collection_documents cd WHERE cd.collection_id = c.id) \
+                     + r.ord)::int, ",
+        );
+        query.push_bind(user_id);
+        query.push(" FROM collections c, unnest(");
+        query.push_bind(document_ids.to_vec());
+        query.push("::uuid[]) WITH ORDINALITY AS r(id, ord) WHERE c.id = ");
+        query.push_bind(collection_id);
+        query.push(" AND r.id IN (SELECT id FROM documents WHERE 1=1");
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::View);
+        query.push(") ON CONFLICT (collection_id, document_id) DO NOTHING");
+
+        let result = query.build().execute(&self.pool).await?;
+        if result.rows_affected() > 0 {
+            self.touch_collection(collection_id).await?;
+        }
+        Ok(result.rows_affected())
+    }
+
+    pub async fn remove_collection_document(&self, collection_id: Uuid, document_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM collection_documents WHERE collection_id = $1 AND document_id = $2")
+            .bind(collection_id)
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() > 0 {
+            self.touch_collection(collection_id).await?;
+        }
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Put the listed documents and subcollections first, in the given order, and the
+    /// rest after them in their current order
+    pub async fn reorder_collection(&self, id: Uuid, document_ids: &[Uuid], collection_ids: &[Uuid]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"WITH requested AS (
+                   SELECT id, ord FROM unnest($2::uuid[]) WITH ORDINALITY AS r(id, ord)
+               ),
+               ranked AS (
+                   SELECT cd.document_id,
+                          (ROW_NUMBER() OVER (ORDER BY r.ord NULLS LAST, cd.position, cd.added_at) - 1)::int AS position
+                   FROM collection_documents cd
+                   LEFT JOIN requested r ON r.id = cd.document_id
+                   WHERE cd.collection_id = $1
+               )
+               UPDATE collection_documents cd SET position = ranked.position
+               FROM ranked
+               WHERE cd.collection_id = $1 AND cd.document_id = ranked.document_id"#,
+        )
+        .bind(id)
+        .bind(document_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"WITH requested AS (
+                   SELECT id, ord FROM unnest($2::uuid[]) WITH ORDINALITY AS r(id, ord)
+               ),
+               ranked AS (
+                   SELECT c.id,
+                          (ROW_NUMBER() OVER (ORDER BY r.ord NULLS LAST, c.position, LOWER(c.name)) - 1)::int AS position
+                   FROM collections c
+                   LEFT JOIN requested r ON r.id = c.id
+                   WHERE c.parent_id = $1
+               )
+               UPDATE collections c SET position = ranked.position
+               FROM ranked
+               WHERE c.id = ranked.id"#,
+        )
+        .bind(id)
+        .bind(collection_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE collections SET updated_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The documents of a collection and its subcollections the user can see, in
+    /// reading order (see [`depth_first_documents`])
+    pub async fn get_collection_tree_document_ids(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        user_role: UserRole,
+    ) -> Result<Vec<Uuid>> {
+        let tree: Vec<(Uuid, Option<Uuid>)> = sqlx::query_as(
+            r#"WITH RECURSIVE tree AS (
+                   SELECT id, parent_id, position, name FROM collections WHERE id = $1
+                   UNION
+                   SELECT c.id, c.parent_id, c.position, c.name FROM collections c JOIN tree t ON c.parent_id = t.id
+               )
+               SELECT id, parent_id FROM tree ORDER BY position, LOWER(name)"#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut children: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for (child_id, parent_id) in &tree {
+            if let Some(parent_id) = parent_id {
+                children.entry(*parent_id).or_default().push(*child_id);
+            }
+        }
+        let collection_ids: Vec<Uuid> = tree.iter().map(|(id, _)| *id).collect();
+
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT cd.collection_id, cd.document_id FROM collection_documents cd WHERE cd.collection_id = ANY(",
+        );
+        query.push_bind(collection_ids);
+        query.push(") AND cd.document_id IN (SELECT id FROM documents WHERE 1=1");
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::View);
+        query.push(") ORDER BY cd.position, cd.added_at");
+        let rows: Vec<(Uuid, Uuid)> = query.build_query_as().fetch_all(&self.pool).await?;
+
+        let mut documents: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for (collection_id, document_id) in rows {
+            documents.entry(collection_id).or_default().push(document_id);
+        }
+
+        Ok(depth_first_documents(id, &children, &documents))
+    }
+
+    async fn touch_collection(&self, id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE collections SET updated_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+
+impl Database {
+    /// Id and stored file path of every document
+    pub async fn get_document_file_paths(&self) -> Result<Vec<(Uuid, String)>> {
+        let rows = sqlx::query_as::<_, (Uuid, String)>("SELECT id, file_path FROM documents")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows)
+    }
+
+    /// Id, stored file path and recorded content hash of every document that has one
+    pub async fn get_document_file_hashes(&self) -> Result<Vec<(Uuid, String, String)>> {
+        let rows = sqlx::query_as::<_, (Uuid, String, String)>(
+            "SELECT id, file_path, file_hash FROM documents WHERE file_hash IS NOT NULL"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Stored file path and size of a document
+    pub async fn get_document_file(&self, document_id: Uuid) -> Result<Option<(String, i64)>> {
+        let row = sqlx::query_as::<_, (String, i64)>("SELECT file_path, file_size FROM documents WHERE id = $1")
+            .bind(document_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row)
+    }
+
+    /// Record the hash of a document's file as it is now stored
+    pub async fn update_document_file_hash(&self, document_id: Uuid, file_hash: &str) -> Result<()> {
+        sqlx::query("UPDATE documents SET file_hash = $2, updated_at = NOW() WHERE id = $1")
+            .bind(document_id)
+            .bind(file_hash)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Whether a stored file named after `id` belongs to a document or document version,
+    /// by id or by the file name of its path
+    pub async fn is_stored_file_referenced(&self, id: Uuid, file_name: &str) -> Result<bool> {
+        let referenced = sqlx::query_scalar::<_, bool>(
+            r#"SELECT EXISTS (SELECT 1 FROM documents WHERE id = $1 OR file_path LIKE '%/' || $2)
+                   OR EXISTS (SELECT 1 FROM document_versions WHERE id = $1)"#
+        )
+        .bind(id)
+        .bind(file_name)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(referenced)
+    }
+
+    /// Flag a document whose file is gone so it is no longer offered for OCR or download
+    pub async fn mark_document_file_missing(&self, document_id: Uuid) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"UPDATE documents
+               SET ocr_status = 'failed',
+                   ocr_failure_reason = 'file_missing',
+                   ocr_error = 'Document file is missing from storage',
+                   updated_at = NOW()
+               WHERE id = $1"#
+        )
+        .bind(document_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM ocr_queue WHERE document_id = $1 AND status IN ('pending', 'failed')")
+            .bind(document_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Graph edges whose source or target node is missing or belongs to another document.
+    /// Returns edge id and the edge's document id.
+    pub async fn find_dangling_graph_edges(&self) -> Result<Vec<(Uuid, Uuid)>> {
+        let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"SELECT e.id, e.document_id
+               FROM document_edges e
+               LEFT JOIN document_nodes s ON s.id = e.source_node_id
+               LEFT JOIN document_nodes t ON t.id = e.target_node_id
+               WHERE s.id IS NULL OR t.id IS NULL
+                  OR s.document_id <> e.document_id
+                  OR t.document_id <> e.document_id"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn delete_graph_edges(&self, edge_ids: &[Uuid]) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM document_edges WHERE id = ANY($1)")
+            .bind(edge_ids)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// OCR queue entries without a document. Returns entry id and the referenced document id.
+    pub async fn find_orphaned_queue_entries(&self) -> Result<Vec<(Uuid, Option<Uuid>)>> {
+        let rows = sqlx::query_as::<_, (Uuid, Option<Uuid>)>(
+            r#"SELECT q.id, q.document_id
+               FROM ocr_queue q
+               LEFT JOIN documents d ON d.id = q.document_id
+               WHERE d.id IS NULL"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn delete_queue_entries(&self, entry_ids: &[Uuid]) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM ocr_queue WHERE id = ANY($1)")
+            .bind(entry_ids)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::documents::{map_row_to_document, DOCUMENT_FIELDS};
+use super::Database;
+use crate::models::{
+    Correspondent, CorrespondentWithCount, CreateCorrespondentRequest, Document, DocumentCorrespondent,
+    UpdateCorrespondentRequest,
+};
+
+const CORRESPONDENT_COLUMNS: &str =
+    "id, user_id, name, match_algorithm, match_pattern, case_sensitive, created_at, updated_at";
+
+impl Database {
+    pub async fn create_correspondent(&self, user_id: Uuid, request: &CreateCorrespondentRequest) -> Result<Correspondent> {
+        let query = format!(
+            r#"INSERT INTO correspondents (user_id, name, match_algorithm, match_pattern, case_sensitive)
+               VALUES ($1, $2, COALESCE($3, 'auto'), $4, COALESCE($5, FALSE))
+               RETURNING {}"#,
+            CORRESPONDENT_COLUMNS
+        );
+
+        let correspondent = sqlx::query_as::<_, Correspondent>(&query)
+            .bind(user_id)
+            .bind(request.name.trim())
+            .bind(&request.match_algorithm)
+            .bind(&request.match_pattern)
+            .bind(request.case_sensitive)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(correspondent)
+    }
+
+    /// The user's correspondents by name
+    pub async fn get_correspondents(&self, user_id: Uuid) -> Result<Vec<Correspondent>> {
+        let query = format!(
+            "SELECT {} FROM correspondents WHERE user_id = $1 ORDER BY LOWER(name)",
+            CORRESPONDENT_COLUMNS
+        );
+        let correspondents = sqlx::query_as::<_, Correspondent>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(correspondents)
+    }
+
+    /// The user's correspondents by name with the number of documents of each
+    pub async fn get_correspondents_with_counts(&self, user_id: Uuid) -> Result<Vec<CorrespondentWithCount>> {
+        let correspondents = sqlx::query_as::<_, CorrespondentWithCount>(
+            r#"SELECT c.id, c.user_id, c.name, c.match_algorithm, c.match_pattern, c.case_sensitive,
+                      c.created_at, c.updated_at, COUNT(dc.document_id) AS document_count
+               FROM correspondents c
+               LEFT JOIN document_correspondents dc ON dc.correspondent_id = c.id
+               WHERE c.user_id = $1
+               GROUP BY c.id
+               ORDER BY LOWER(c.name)"#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(correspondents)
+    }
+
+    pub async fn get_correspondent(&self, id: Uuid, user_id: Uuid) -> Result<Option<Correspondent>> {
+        let query = format!(
+            "SELECT {} FROM correspondents WHERE id = $1 AND user_id = $2",
+            CORRESPONDENT_COLUMNS
+        );
+        let correspondent = sqlx::query_as::<_, Correspondent>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(correspondent)
+    }
+
+    pub async fn get_correspondent_by_name(&self, user_id: Uuid, name: &str) -> Result<Option<Correspondent>> {
+        let query = format!(
+            "SELECT {} FROM correspondents WHERE user_id = $1 AND LOWER(name) = LOWER($2)",
+            CORRESPONDENT_COLUMNS
+        );
+        let correspondent = sqlx::query_as::<_, Correspondent>(&query)
+            .bind(user_id)
+            .bind(name.trim())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(correspondent)
+    }
+
+    /// Id of the user's correspondent with this name, created if it does not exist
+    pub async fn ensure_correspondent(&self, user_id: Uuid, name: &str) -> Result<Uuid> {
+        let correspondent_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO correspondents (user_id, name)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id, (LOWER(name))) DO UPDATE SET name = correspondents.name
+            RETURNING id
+            "#
+        )
+        .bind(user_id)
+        .bind(name.trim())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(correspondent_id)
+    }
+
+    /// Whether another of the user's correspondents has this name, ignoring case
+    pub async fn correspondent_name_taken(&self, user_id: Uuid, name: &str, except: Option<Uuid>) -> Result<bool> {
+        let taken = sqlx::query_scalar::<_, bool>(
+            r#"SELECT EXISTS(
+                   SELECT 1 FROM correspondents
+                   WHERE user_id = $1 AND LOWER(name) = LOWER($2) AND ($3::uuid IS NULL OR id <> $3)
+               )"#,
+        )
+        .bind(user_id)
+        .bind(name.trim())
+        .bind(except)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(taken)
+    }
+
+    pub async fn update_correspondent(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        request: &UpdateCorrespondentRequest,
+    ) -> Result<Option<Correspondent>> {
+        let query = format!(
+            r#"UPDATE correspondents
+               SET name = COALESCE($3, name),
+                   match_algorithm = COALESCE($4, match_algorithm),
+                   match_pattern = COALESCE($5, match_pattern),
+                   case_sensitive = COALESCE($6, case_sensitive),
+                   updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            CORRESPONDENT_COLUMNS
+        );
+        let correspondent = sqlx::query_as::<_, Correspondent>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(request.name.as_deref().map(str::trim))
+            .bind(&request.match_algorithm)
+            .bind(&request.match_pattern)
+            .bind(request.case_sensitive)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(correspondent)
+    }
+
+    /// Deletes a correspondent; its documents no longer have one
+    pub async fn delete_correspondent(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM correspondents WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Gives the user's documents the correspondent, or clears theirs when None.
+    /// Automatic assignments never replace a manual one. Returns how many documents changed.
+    pub async fn assign_correspondent(
+        &self,
+        user_id: Uuid,
+        document_ids: &[Uuid],
+        correspondent_id: Option<Uuid>,
+        assigned_by: &str,
+    ) -> Result<i64> {
+        let result = match correspondent_id {
+            Some(correspondent_id) => {
+                sqlx::query(
+                    r#"INSERT INTO document_correspondents (document_id, correspondent_id, assigned_by)
+                       SELECT id, $3, $4 FROM documents WHERE id = ANY($1) AND user_id = $2
+                       ON CONFLICT (document_id) DO UPDATE
+                       SET correspondent_id = EXCLUDED.correspondent_id,
+                           assigned_by = EXCLUDED.assigned_by,
+                           assigned_at = NOW()
+                       WHERE document_correspondents.assigned_by <> 'manual' OR EXCLUDED.assigned_by = 'manual'"#
+                )
+                .bind(document_ids)
+                .bind(user_id)
+                .bind(correspondent_id)
+                .bind(assigned_by)
+                .execute(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query(
+                    r#"DELETE FROM document_correspondents
+                       WHERE document_id IN (SELECT id FROM documents WHERE id = ANY($1) AND user_id = $2)"#
+                )
+                .bind(document_ids)
+                .bind(user_id)
+                .execute(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    pub async fn get_document_correspondent(&self, document_id: Uuid) -> Result<Option<DocumentCorrespondent>> {
+        let correspondent = sqlx::query_as::<_, DocumentCorrespondent>(
+            r#"SELECT dc.document_id, dc.correspondent_id, c.name, dc.assigned_by, dc.assigned_at
+               FROM document_correspondents dc
+               JOIN correspondents c ON c.id = dc.correspondent_id
+               WHERE dc.document_id = $1"#,
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(correspondent)
+    }
+
+    /// A batch of the user's documents without a correspondent, in id order after `after`
+    pub async fn get_documents_without_correspondent(
+        &self,
+        user_id: Uuid,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Document>> {
+        let query = format!(
+            r#"SELECT {} FROM documents
+               WHERE user_id = $1
+                 AND ($2::uuid IS NULL OR id > $2)
+                 AND NOT EXISTS (SELECT 1 FROM document_correspondents dc WHERE dc.document_id = documents.id)
+               ORDER BY id
+               LIMIT $3"#,
+            DOCUMENT_FIELDS
+        );
+        let rows = sqlx::query(&query)
+            .bind(user_id)
+            .bind(after)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(map_row_to_document).collect())
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{DigestDocument, DigestFailure, DigestSearchMatch, DigestSettings, UpdateDigestSettingsRequest};
+
+const DIGEST_SETTINGS_COLUMNS: &str =
+    "user_id, frequency, send_hour, weekday, skip_empty, last_sent_at, last_error, created_at, updated_at";
+
+impl Database {
+    pub async fn get_digest_settings(&self, user_id: Uuid) -> Result<Option<DigestSettings>> {
+        let query = format!("SELECT {} FROM digest_settings WHERE user_id = $1", DIGEST_SETTINGS_COLUMNS);
+        let settings = sqlx::query_as::<_, DigestSettings>(&query)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(settings)
+    }
+
+    /// Create or change a user's digest settings; fields the request leaves out keep
+    /// their value
+    pub async fn save_digest_settings(
+        &self,
+        user_id: Uuid,
+        request: &UpdateDigestSettingsRequest,
+    ) -> Result<DigestSettings> {
+        let query = format!(
+            r#"INSERT INTO digest_settings (user_id, frequency, send_hour, weekday, skip_empty)
+               VALUES ($1, COALESCE($2, 'off'), COALESCE($3, 7), COALESCE($4, 0), COALESCE($5, TRUE))
+               ON CONFLICT (user_id) DO UPDATE SET
+                   frequency = COALESCE($2, digest_settings.frequency),
+                   send_hour = COALESCE($3, digest_settings.send_hour),
+                   weekday = COALESCE($4, digest_settings.weekday),
+                   skip_empty = COALESCE($5, digest_settings.skip_empty),
+                   updated_at = NOW()
+               RETURNING {}"#,
+            DIGEST_SETTINGS_COLUMNS
+        );
+        let settings = sqlx::query_as::<_, DigestSettings>(&query)
+            .bind(user_id)
+            .bind(request.frequency.map(|frequency| frequency.to_string()))
+            .bind(request.send_hour)
+            .bind(request.weekday)
+            .bind(request.skip_empty)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(settings)
+    }
+
+    /// Settings of every user who gets a digest
+    pub async fn get_active_digest_settings(&self) -> Result<Vec<DigestSettings>> {
+        let query = format!(
+            "SELECT {} FROM digest_settings WHERE frequency <> 'off'",
+            DIGEST_SETTINGS_COLUMNS
+        );
+        let settings = sqlx::query_as::<_, DigestSettings>(&query).fetch_all(&self.pool).await?;
+
+        Ok(settings)
+    }
+
+    /// Mark the digest up to `sent_at` as sent, unless another instance already did.
+    /// Returns whether this call claimed it.
+    pub async fn claim_digest(
+        &self,
+        user_id: Uuid,
+        previous: Option<DateTime<Utc>>,
+        sent_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"UPDATE digest_settings SET last_sent_at = $3
+               WHERE user_id = $1 AND last_sent_at IS NOT DISTINCT FROM $2"#,
+        )
+        .bind(user_id)
+        .bind(previous)
+        .bind(sent_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn record_digest_error(&self, user_id: Uuid, error: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE digest_settings SET last_error = $2 WHERE user_id = $1")
+            .bind(user_id)
+            .bind(error)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Documents of the user created in the period, newest first, and how many there are
+    pub async fn get_digest_documents(
+        &self,
+        user_id: Uuid,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<(Vec<DigestDocument>, i64)> {
+        let documents = sqlx::query_as::<_, DigestDocument>(
+            r#"SELECT id, original_filename, created_at FROM documents
+               WHERE user_id = $1 AND created_at > $2 AND created_at <= $3
+               ORDER BY created_at DESC
+               LIMIT $4"#,
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(until)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM documents WHERE user_id = $1 AND created_at > $2 AND created_at <= $3",
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(until)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((documents, count))
+    }
+
+    /// Files of the user that failed in the period, newest first, and how many there are
+    pub async fn get_digest_failures(
+        &self,
+        user_id: Uuid,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<(Vec<DigestFailure>, i64)> {
+        let failures = sqlx::query_as::<_, DigestFailure>(
+            r#"SELECT id, COALESCE(original_filename, filename) AS filename, failure_reason, failure_stage, created_at
+               FROM failed_documents
+               WHERE user_id = $1 AND created_at > $2 AND created_at <= $3
+               ORDER BY created_at DESC
+               LIMIT $4"#,
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(until)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM failed_documents WHERE user_id = $1 AND created_at > $2 AND created_at <= $3",
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(until)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((failures, count))
+    }
+
+    /// The user's saved searches that matched documents in the period, from the match
+    /// notifications they were sent; most matches first
+    pub async fn get_digest_search_matches(
+        &self,
+        user_id: Uuid,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<DigestSearchMatch>> {
+        let matches = sqlx::query_as::<_, DigestSearchMatch>(
+            r#"SELECT s.id AS saved_search_id, s.name, COUNT(*) AS matches
+               FROM notifications n
+               JOIN saved_searches s ON s.id = (n.metadata->>'saved_search_id')::uuid
+               WHERE n.user_id = $1 AND s.user_id = $1
+                 AND n.metadata ? 'saved_search_id'
+                 AND n.created_at > $2 AND n.created_at <= $3
+               GROUP BY s.id, s.name
+               ORDER BY matches DESC, s.name"#,
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(matches)
+    }
+}
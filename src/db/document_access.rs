@@ -0,0 +1,202 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{AccessBucket, AccessedDocument, StatisticsBucket, UnusedDocument, UserAccessStatistics};
+
+/// Access rows `a` of documents `d` of user $1, or of everyone when it is null, of
+/// document $4 when it is not null, from day $2 to day $3 (both included)
+const ACCESS_IN_RANGE: &str = r#"($1::uuid IS NULL OR d.user_id = $1)
+    AND ($4::uuid IS NULL OR a.document_id = $4)
+    AND a.day >= $2::date AND a.day <= $3::date"#;
+
+impl Database {
+    /// Count a view or download of a document today (UTC); `user_id` is None for
+    /// visitors without an account
+    pub async fn record_document_access(
+        &self,
+        document_id: Uuid,
+        user_id: Option<Uuid>,
+        views: i64,
+        downloads: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO document_access_daily (document_id, user_id, day, views, downloads)
+               VALUES ($1, $2, (NOW() AT TIME ZONE 'UTC')::date, $3, $4)
+               ON CONFLICT (document_id, COALESCE(user_id, '00000000-0000-0000-0000-000000000000'::uuid), day)
+               DO UPDATE SET views = document_access_daily.views + EXCLUDED.views,
+                             downloads = document_access_daily.downloads + EXCLUDED.downloads"#
+        )
+        .bind(document_id)
+        .bind(user_id)
+        .bind(views)
+        .bind(downloads)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Views and downloads per bucket, with empty buckets
+    pub async fn get_access_activity(
+        &self,
+        user_id: Option<Uuid>,
+        document_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+        bucket: StatisticsBucket,
+    ) -> Result<Vec<AccessBucket>> {
+        let query = format!(
+            r#"WITH buckets AS (
+                   SELECT generate_series(date_trunc($5, $2::date::timestamp), $3::date::timestamp, ('1 ' || $5)::interval)::date AS start
+               )
+               SELECT b.start,
+                      COALESCE(SUM(a.views), 0)::bigint AS views,
+                      COALESCE(SUM(a.downloads), 0)::bigint AS downloads,
+                      COUNT(DISTINCT a.document_id) AS documents,
+                      COUNT(DISTINCT a.user_id) AS users
+               FROM buckets b
+               LEFT JOIN (document_access_daily a JOIN documents d ON d.id = a.document_id)
+                 ON date_trunc($5, a.day::timestamp)::date = b.start AND {}
+               GROUP BY b.start
+               ORDER BY b.start"#,
+            ACCESS_IN_RANGE
+        );
+        let buckets = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, AccessBucket>(&query)
+                    .bind(user_id)
+                    .bind(from)
+                    .bind(to)
+                    .bind(document_id)
+                    .bind(bucket.to_string());
+                async move { query.fetch_all(&pool).await }
+            })
+            .await?;
+
+        Ok(buckets)
+    }
+
+    /// The documents opened most often in the range
+    pub async fn get_most_accessed_documents(
+        &self,
+        user_id: Option<Uuid>,
+        document_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+        limit: i64,
+    ) -> Result<Vec<AccessedDocument>> {
+        let query = format!(
+            r#"SELECT d.id AS document_id, d.original_filename AS filename,
+                      d.user_id AS owner_id, u.username AS owner_username,
+                      SUM(a.views)::bigint AS views, SUM(a.downloads)::bigint AS downloads,
+                      COUNT(DISTINCT a.user_id) AS users,
+                      (SELECT MAX(l.day) FROM document_access_daily l WHERE l.document_id = d.id) AS last_accessed
+               FROM document_access_daily a
+               JOIN documents d ON d.id = a.document_id
+               JOIN users u ON u.id = d.user_id
+               WHERE {}
+               GROUP BY d.id, u.username
+               ORDER BY SUM(a.views + a.downloads) DESC, d.id
+               LIMIT $5"#,
+            ACCESS_IN_RANGE
+        );
+        let documents = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, AccessedDocument>(&query)
+                    .bind(user_id)
+                    .bind(from)
+                    .bind(to)
+                    .bind(document_id)
+                    .bind(limit);
+                async move { query.fetch_all(&pool).await }
+            })
+            .await?;
+
+        Ok(documents)
+    }
+
+    /// What each signed-in user opened in the range
+    pub async fn get_user_access_statistics(
+        &self,
+        user_id: Option<Uuid>,
+        document_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<UserAccessStatistics>> {
+        let query = format!(
+            r#"SELECT u.id AS user_id, u.username,
+                      SUM(a.views)::bigint AS views, SUM(a.downloads)::bigint AS downloads,
+                      COUNT(DISTINCT a.document_id) AS documents,
+                      (SELECT MAX(l.day) FROM document_access_daily l WHERE l.user_id = u.id) AS last_active
+               FROM document_access_daily a
+               JOIN documents d ON d.id = a.document_id
+               JOIN users u ON u.id = a.user_id
+               WHERE {}
+               GROUP BY u.id, u.username
+               ORDER BY views DESC, u.username"#,
+            ACCESS_IN_RANGE
+        );
+        let users = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, UserAccessStatistics>(&query)
+                    .bind(user_id)
+                    .bind(from)
+                    .bind(to)
+                    .bind(document_id);
+                async move { query.fetch_all(&pool).await }
+            })
+            .await?;
+
+        Ok(users)
+    }
+
+    /// Documents uploaded before `uploaded_before` that nobody opened since, oldest
+    /// first, with how many there are and the bytes they take
+    pub async fn get_unused_documents(
+        &self,
+        user_id: Option<Uuid>,
+        uploaded_before: DateTime<Utc>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<UnusedDocument>, i64, i64)> {
+        const UNUSED: &str = r#"($1::uuid IS NULL OR d.user_id = $1)
+            AND d.created_at < $2
+            AND NOT EXISTS (SELECT 1 FROM document_access_daily a WHERE a.document_id = d.id)"#;
+
+        let list_query = format!(
+            r#"SELECT d.id AS document_id, d.original_filename AS filename,
+                      d.user_id AS owner_id, u.username AS owner_username, d.file_size, d.created_at
+               FROM documents d
+               JOIN users u ON u.id = d.user_id
+               WHERE {}
+               ORDER BY d.created_at, d.id
+               LIMIT $3 OFFSET $4"#,
+            UNUSED
+        );
+        let total_query = format!(
+            "SELECT COUNT(*), COALESCE(SUM(d.file_size), 0)::bigint FROM documents d WHERE {}",
+            UNUSED
+        );
+
+        let documents = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, UnusedDocument>(&list_query)
+                    .bind(user_id)
+                    .bind(uploaded_before)
+                    .bind(limit)
+                    .bind(offset);
+                async move { query.fetch_all(&pool).await }
+            })
+            .await?;
+        let (total, total_bytes) = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, (i64, i64)>(&total_query).bind(user_id).bind(uploaded_before);
+                async move { query.fetch_one(&pool).await }
+            })
+            .await?;
+
+        Ok((documents, total, total_bytes))
+    }
+}
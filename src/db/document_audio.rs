@@ -0,0 +1,116 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{AudioStatus, DocumentAudio};
+
+const AUDIO_COLUMNS: &str = "id, document_id, status, voice, model, source_chars, file_path, file_size, \
+    error, requested_by, created_at, started_at, completed_at";
+
+impl Database {
+    /// Queues a narration of the document, replacing a finished one. Returns None
+    /// while an earlier narration of the document is still queued or running.
+    pub async fn queue_document_audio(
+        &self,
+        document_id: Uuid,
+        voice: &str,
+        model: &str,
+        source_chars: i32,
+        requested_by: Uuid,
+    ) -> Result<Option<DocumentAudio>> {
+        let query = format!(
+            r#"INSERT INTO document_audio (document_id, voice, model, source_chars, requested_by)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (document_id) DO UPDATE
+               SET status = 'queued', voice = EXCLUDED.voice, model = EXCLUDED.model,
+                   source_chars = EXCLUDED.source_chars, requested_by = EXCLUDED.requested_by,
+                   error = NULL, created_at = NOW(), started_at = NULL, completed_at = NULL
+               WHERE document_audio.status NOT IN ('queued', 'running')
+               RETURNING {}"#,
+            AUDIO_COLUMNS
+        );
+        let audio = sqlx::query_as::<_, DocumentAudio>(&query)
+            .bind(document_id)
+            .bind(voice)
+            .bind(model)
+            .bind(source_chars)
+            .bind(requested_by)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(audio)
+    }
+
+    pub async fn get_document_audio(&self, document_id: Uuid) -> Result<Option<DocumentAudio>> {
+        let query = format!("SELECT {} FROM document_audio WHERE document_id = $1", AUDIO_COLUMNS);
+        let audio = sqlx::query_as::<_, DocumentAudio>(&query)
+            .bind(document_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(audio)
+    }
+
+    /// Narrations that were queued or running when the server stopped, oldest first
+    pub async fn get_unfinished_document_audio(&self) -> Result<Vec<DocumentAudio>> {
+        let query = format!(
+            "SELECT {} FROM document_audio WHERE status IN ('queued', 'running') ORDER BY created_at",
+            AUDIO_COLUMNS
+        );
+        let audio = sqlx::query_as::<_, DocumentAudio>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(audio)
+    }
+
+    pub async fn start_document_audio(&self, document_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE document_audio SET status = 'running', started_at = NOW() WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records how a narration ended. A failed narration keeps the audio of the
+    /// last one. Returns None when the narration was removed in the meantime.
+    pub async fn finish_document_audio(
+        &self,
+        document_id: Uuid,
+        status: AudioStatus,
+        file_path: Option<&str>,
+        file_size: Option<i64>,
+        error: Option<&str>,
+    ) -> Result<Option<DocumentAudio>> {
+        let query = format!(
+            r#"UPDATE document_audio
+               SET status = $2, file_path = COALESCE($3, file_path), file_size = COALESCE($4, file_size),
+                   error = $5, completed_at = NOW()
+               WHERE document_id = $1
+               RETURNING {}"#,
+            AUDIO_COLUMNS
+        );
+        let audio = sqlx::query_as::<_, DocumentAudio>(&query)
+            .bind(document_id)
+            .bind(status.to_string())
+            .bind(file_path)
+            .bind(file_size)
+            .bind(error)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(audio)
+    }
+
+    /// Removes the document's narration and returns it, so its file can be deleted
+    pub async fn delete_document_audio(&self, document_id: Uuid) -> Result<Option<DocumentAudio>> {
+        let query = format!("DELETE FROM document_audio WHERE document_id = $1 RETURNING {}", AUDIO_COLUMNS);
+        let audio = sqlx::query_as::<_, DocumentAudio>(&query)
+            .bind(document_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(audio)
+    }
+}
@@ -0,0 +1,22 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+
+impl Database {
+    /// Record where a document was captured, replacing any earlier position
+    pub async fn set_document_coordinates(&self, document_id: Uuid, latitude: f64, longitude: f64) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO document_coordinates (document_id, latitude, longitude)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (document_id) DO UPDATE SET latitude = EXCLUDED.latitude, longitude = EXCLUDED.longitude"#,
+        )
+        .bind(document_id)
+        .bind(latitude)
+        .bind(longitude)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
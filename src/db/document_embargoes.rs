@@ -0,0 +1,166 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::documents::embargoed_condition;
+use super::Database;
+use crate::models::{DocumentEmbargo, SourceEmbargo};
+
+const EMBARGO_FIELDS: &str = r#"
+    e.document_id, d.original_filename, e.embargoed_until, e.reason, e.source_id,
+    e.created_by, e.created_at, e.released_at, e.released_by
+"#;
+
+const ACTIVE_CONDITION: &str = "e.released_at IS NULL AND (e.embargoed_until IS NULL OR e.embargoed_until > NOW())";
+
+impl Database {
+    /// Embargoes the documents, replacing any embargo they had. Returns the documents
+    /// embargoed; ids of documents that do not exist are left out.
+    pub async fn embargo_documents(
+        &self,
+        document_ids: &[Uuid],
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        reason: Option<&str>,
+        created_by: Uuid,
+    ) -> Result<Vec<Uuid>> {
+        let embargoed = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO document_embargoes (document_id, embargoed_until, reason, created_by)
+            SELECT id, $2, $3, $4 FROM documents WHERE id = ANY($1)
+            ON CONFLICT (document_id) DO UPDATE SET
+                embargoed_until = EXCLUDED.embargoed_until,
+                reason = EXCLUDED.reason,
+                source_id = NULL,
+                created_by = EXCLUDED.created_by,
+                created_at = NOW(),
+                released_at = NULL,
+                released_by = NULL
+            RETURNING document_id
+            "#,
+        )
+        .bind(document_ids)
+        .bind(until)
+        .bind(reason)
+        .bind(created_by)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(embargoed)
+    }
+
+    /// Embargoes a document just ingested from a source that embargoes what it ingests.
+    /// Returns false when the source has no embargo.
+    pub async fn apply_source_embargo(&self, document_id: Uuid, source_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO document_embargoes (document_id, embargoed_until, reason, source_id, created_by)
+            SELECT $1, NOW() + make_interval(days => s.embargo_days), s.reason, s.source_id, s.updated_by
+            FROM source_embargoes s
+            WHERE s.source_id = $2
+            ON CONFLICT (document_id) DO NOTHING
+            "#,
+        )
+        .bind(document_id)
+        .bind(source_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Releases the documents' active embargoes. Returns the documents released.
+    pub async fn release_embargoes(&self, document_ids: &[Uuid], released_by: Uuid) -> Result<Vec<Uuid>> {
+        let query = format!(
+            r#"UPDATE document_embargoes e SET released_at = NOW(), released_by = $2
+               WHERE e.document_id = ANY($1) AND {}
+               RETURNING e.document_id"#,
+            ACTIVE_CONDITION
+        );
+        let released = sqlx::query_scalar::<_, Uuid>(&query)
+            .bind(document_ids)
+            .bind(released_by)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(released)
+    }
+
+    /// Embargoes by date, those ending soonest first and those without a date last;
+    /// only active ones unless `include_inactive`
+    pub async fn list_embargoes(&self, include_inactive: bool, limit: i64, offset: i64) -> Result<Vec<DocumentEmbargo>> {
+        let query = format!(
+            r#"SELECT {} FROM document_embargoes e
+               JOIN documents d ON d.id = e.document_id
+               WHERE $1 OR ({})
+               ORDER BY e.embargoed_until ASC NULLS LAST, e.created_at DESC
+               LIMIT $2 OFFSET $3"#,
+            EMBARGO_FIELDS, ACTIVE_CONDITION
+        );
+        let embargoes = sqlx::query_as::<_, DocumentEmbargo>(&query)
+            .bind(include_inactive)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(embargoes)
+    }
+
+    /// The document's embargo, active or not
+    pub async fn get_document_embargo(&self, document_id: Uuid) -> Result<Option<DocumentEmbargo>> {
+        let query = format!(
+            "SELECT {} FROM document_embargoes e JOIN documents d ON d.id = e.document_id WHERE e.document_id = $1",
+            EMBARGO_FIELDS
+        );
+        Ok(sqlx::query_as::<_, DocumentEmbargo>(&query).bind(document_id).fetch_optional(&self.pool).await?)
+    }
+
+    /// Whether the document is under an active embargo
+    pub async fn is_embargoed(&self, document_id: Uuid) -> Result<bool> {
+        let query = format!("SELECT {}", embargoed_condition("$1::uuid"));
+        Ok(sqlx::query_scalar::<_, bool>(&query).bind(document_id).fetch_one(&self.pool).await?)
+    }
+
+    pub async fn get_source_embargo(&self, source_id: Uuid) -> Result<Option<SourceEmbargo>> {
+        let embargo = sqlx::query_as::<_, SourceEmbargo>(
+            "SELECT source_id, embargo_days, reason, updated_by, updated_at FROM source_embargoes WHERE source_id = $1",
+        )
+        .bind(source_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(embargo)
+    }
+
+    pub async fn set_source_embargo(
+        &self,
+        source_id: Uuid,
+        embargo_days: Option<i32>,
+        reason: Option<&str>,
+        updated_by: Uuid,
+    ) -> Result<SourceEmbargo> {
+        let embargo = sqlx::query_as::<_, SourceEmbargo>(
+            r#"
+            INSERT INTO source_embargoes (source_id, embargo_days, reason, updated_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (source_id) DO UPDATE SET
+                embargo_days = EXCLUDED.embargo_days,
+                reason = EXCLUDED.reason,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            RETURNING source_id, embargo_days, reason, updated_by, updated_at
+            "#,
+        )
+        .bind(source_id)
+        .bind(embargo_days)
+        .bind(reason)
+        .bind(updated_by)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(embargo)
+    }
+
+    /// Stops embargoing what the source ingests; documents already embargoed stay so.
+    /// Returns false if the source had no embargo.
+    pub async fn delete_source_embargo(&self, source_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM source_embargoes WHERE source_id = $1")
+            .bind(source_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
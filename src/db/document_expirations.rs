@@ -0,0 +1,168 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{DocumentExpiration, UpcomingExpirationsQuery, EXPIRATION_SOURCE_EXTRACTED, EXPIRATION_SOURCE_MANUAL};
+
+const EXPIRATION_SELECT: &str = r#"
+    SELECT e.document_id, d.original_filename AS document_filename, d.user_id, e.expires_on, e.kind,
+           e.notice_days, e.note, e.source, e.notified_at, (e.expires_on - CURRENT_DATE) AS days_left,
+           e.set_by, e.created_at, e.updated_at
+    FROM document_expirations e
+    JOIN documents d ON d.id = e.document_id
+"#;
+
+impl Database {
+    pub async fn get_document_expiration(&self, document_id: Uuid) -> Result<Option<DocumentExpiration>> {
+        let expiration = sqlx::query_as::<_, DocumentExpiration>(&format!("{} WHERE e.document_id = $1", EXPIRATION_SELECT))
+            .bind(document_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(expiration)
+    }
+
+    /// Expirations of the user's documents up to `within_days` from today, soonest first
+    pub async fn get_upcoming_expirations(&self, user_id: Uuid, query: &UpcomingExpirationsQuery) -> Result<Vec<DocumentExpiration>> {
+        let expirations = sqlx::query_as::<_, DocumentExpiration>(&format!(
+            r#"{} WHERE d.user_id = $1
+                  AND e.expires_on <= CURRENT_DATE + $2::INTEGER
+                  AND ($3 OR e.expires_on >= CURRENT_DATE)
+                  AND ($4::TEXT IS NULL OR e.kind = $4)
+               ORDER BY e.expires_on, d.original_filename"#,
+            EXPIRATION_SELECT
+        ))
+        .bind(user_id)
+        .bind(query.within_days() as i32)
+        .bind(query.include_expired)
+        .bind(&query.kind)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(expirations)
+    }
+
+    /// Set a document's expiration by hand. The notification goes out again when the
+    /// date or notice period changed.
+    pub async fn set_document_expiration(
+        &self,
+        document_id: Uuid,
+        expires_on: NaiveDate,
+        kind: &str,
+        notice_days: i32,
+        note: Option<&str>,
+        set_by: Uuid,
+    ) -> Result<DocumentExpiration> {
+        sqlx::query(
+            r#"INSERT INTO document_expirations (document_id, expires_on, kind, notice_days, note, source, set_by)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)
+               ON CONFLICT (document_id) DO UPDATE SET
+                   expires_on = EXCLUDED.expires_on, kind = EXCLUDED.kind, notice_days = EXCLUDED.notice_days,
+                   note = EXCLUDED.note, source = EXCLUDED.source, set_by = EXCLUDED.set_by,
+                   notified_at = CASE
+                       WHEN document_expirations.expires_on = EXCLUDED.expires_on
+                            AND document_expirations.notice_days = EXCLUDED.notice_days
+                       THEN document_expirations.notified_at
+                   END,
+                   updated_at = NOW()"#,
+        )
+        .bind(document_id)
+        .bind(expires_on)
+        .bind(kind)
+        .bind(notice_days)
+        .bind(note)
+        .bind(EXPIRATION_SOURCE_MANUAL)
+        .bind(set_by)
+        .execute(&self.pool)
+        .await?;
+
+        self.get_document_expiration(document_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Expiration of document {} disappeared after it was set", document_id))
+    }
+
+    /// Record an expiration found in a document's text, unless one was set by hand.
+    /// Whether it was recorded.
+    pub async fn record_extracted_expiration(
+        &self,
+        document_id: Uuid,
+        expires_on: NaiveDate,
+        kind: &str,
+        notice_days: i32,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"INSERT INTO document_expirations (document_id, expires_on, kind, notice_days, source)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (document_id) DO UPDATE SET
+                   expires_on = EXCLUDED.expires_on, kind = EXCLUDED.kind,
+                   notified_at = CASE
+                       WHEN document_expirations.expires_on = EXCLUDED.expires_on THEN document_expirations.notified_at
+                   END,
+                   updated_at = NOW()
+               WHERE document_expirations.source = $5"#,
+        )
+        .bind(document_id)
+        .bind(expires_on)
+        .bind(kind)
+        .bind(notice_days)
+        .bind(EXPIRATION_SOURCE_EXTRACTED)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete_document_expiration(&self, document_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM document_expirations WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark up to `limit` expirations whose notice period started as notified and
+    /// return them, soonest first. Dates already past are not announced. Rows another
+    /// instance is claiming at the same time are skipped, so each owner is told once.
+    pub async fn claim_due_expirations(&self, limit: i64) -> Result<Vec<DocumentExpiration>> {
+        let expirations = sqlx::query_as::<_, DocumentExpiration>(
+            r#"WITH due AS (
+                   UPDATE document_expirations
+                   SET notified_at = NOW()
+                   WHERE document_id IN (
+                       SELECT document_id FROM document_expirations
+                       WHERE notified_at IS NULL
+                         AND expires_on >= CURRENT_DATE
+                         AND expires_on - notice_days <= CURRENT_DATE
+                       ORDER BY expires_on
+                       LIMIT $1
+                       FOR UPDATE SKIP LOCKED
+                   )
+                   RETURNING *
+               )
+               SELECT due.document_id, d.original_filename AS document_filename, d.user_id, due.expires_on,
+                      due.kind, due.notice_days, due.note, due.source, due.notified_at,
+                      (due.expires_on - CURRENT_DATE) AS days_left, due.set_by, due.created_at, due.updated_at
+               FROM due
+               JOIN documents d ON d.id = due.document_id
+               ORDER BY due.expires_on"#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(expirations)
+    }
+
+    /// Undo the claim of an expiration whose notification could not be created, so the
+    /// next run tries again
+    pub async fn release_expiration(&self, document_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE document_expirations SET notified_at = NULL WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
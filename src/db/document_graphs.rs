@@ -0,0 +1,125 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    apply_graph_corrections, DocumentGraph, DocumentGraphEdge, DocumentGraphNode, GraphChange, GraphCorrection,
+    GraphVersion,
+};
+
+const GRAPH_VERSION_COLUMNS: &str = "id, document_id, version_number, model, node_count, edge_count, created_at";
+
+impl Database {
+    /// The document's graph versions, newest first
+    pub async fn list_graph_versions(&self, document_id: Uuid) -> Result<Vec<GraphVersion>> {
+        let query = format!(
+            "SELECT {} FROM document_graph_versions WHERE document_id = $1 ORDER BY version_number DESC",
+            GRAPH_VERSION_COLUMNS
+        );
+        let versions = sqlx::query_as::<_, GraphVersion>(&query)
+            .bind(document_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(versions)
+    }
+
+    /// The given version of the document's graph, or the latest
+    pub async fn get_graph_version(&self, document_id: Uuid, version_number: Option<i32>) -> Result<Option<GraphVersion>> {
+        let query = format!(
+            r#"SELECT {} FROM document_graph_versions
+               WHERE document_id = $1 AND ($2::int IS NULL OR version_number = $2)
+               ORDER BY version_number DESC
+               LIMIT 1"#,
+            GRAPH_VERSION_COLUMNS
+        );
+        let version = sqlx::query_as::<_, GraphVersion>(&query)
+            .bind(document_id)
+            .bind(version_number)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(version)
+    }
+
+    /// The document's graph as users see it: the version, or the latest, with every
+    /// correction applied
+    pub async fn get_document_graph(&self, document_id: Uuid, version_number: Option<i32>) -> Result<DocumentGraph> {
+        let Some(version) = self.get_graph_version(document_id, version_number).await? else {
+            return Ok(DocumentGraph { version: None, nodes: Vec::new(), edges: Vec::new(), corrections_applied: 0 });
+        };
+
+        let mut nodes = sqlx::query_as::<_, DocumentGraphNode>(
+            "SELECT id, label, name, properties FROM document_nodes WHERE version_id = $1 ORDER BY created_at, id"
+        )
+        .bind(version.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut edges = sqlx::query_as::<_, DocumentGraphEdge>(
+            r#"SELECT id, source_node_id, target_node_id, relationship, properties
+               FROM document_edges WHERE version_id = $1 ORDER BY created_at, id"#
+        )
+        .bind(version.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let corrections = self.list_graph_corrections(document_id).await?;
+        let corrections_applied =
+            apply_graph_corrections(&mut nodes, &mut edges, corrections.iter().map(|c| &c.change));
+
+        Ok(DocumentGraph { version: Some(version), nodes, edges, corrections_applied })
+    }
+
+    /// The document's corrections, oldest first, in the order they are applied
+    pub async fn list_graph_corrections(&self, document_id: Uuid) -> Result<Vec<GraphCorrection>> {
+        let corrections = sqlx::query_as::<_, GraphCorrection>(
+            "SELECT * FROM document_graph_corrections WHERE document_id = $1 ORDER BY created_at, id"
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(corrections)
+    }
+
+    pub async fn create_graph_correction(
+        &self,
+        document_id: Uuid,
+        change: &GraphChange,
+        created_by: Uuid,
+    ) -> Result<GraphCorrection> {
+        let correction = sqlx::query_as::<_, GraphCorrection>(
+            r#"INSERT INTO document_graph_corrections
+                   (document_id, kind, node_label, node_name, new_label, new_name,
+                    source_name, target_name, relationship, created_by)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+               RETURNING *"#
+        )
+        .bind(document_id)
+        .bind(change.kind.to_string())
+        .bind(&change.node_label)
+        .bind(&change.node_name)
+        .bind(&change.new_label)
+        .bind(&change.new_name)
+        .bind(&change.source_name)
+        .bind(&change.target_name)
+        .bind(&change.relationship)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(correction)
+    }
+
+    /// Undoes a correction. Returns false if the document has no such correction.
+    pub async fn delete_graph_correction(&self, document_id: Uuid, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM document_graph_corrections WHERE id = $1 AND document_id = $2")
+            .bind(id)
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::documents::apply_shared_access_filter;
+use super::entities::LATEST_NODES;
+use super::Database;
+use crate::models::{
+    CreateDocumentRelationRequest, DocumentRelation, DocumentRelationSuggestion, LinkedDocumentRelation, SharePermission,
+    UpdateDocumentRelationRequest, UserRole,
+};
+
+const DOCUMENT_RELATION_COLUMNS: &str =
+    "id, source_document_id, target_document_id, relation_type, note, created_by, created_at, updated_at";
+
+#[derive(sqlx::FromRow)]
+struct GraphRelatedDocument {
+    document_id: Uuid,
+    filename: String,
+    shared_entities: Vec<String>,
+    relationships: Vec<String>,
+}
+
+impl Database {
+    /// None when the relation already exists
+    pub async fn create_document_relation(
+        &self,
+        source_document_id: Uuid,
+        request: &CreateDocumentRelationRequest,
+        created_by: Uuid,
+    ) -> Result<Option<DocumentRelation>> {
+        let query = format!(
+            r#"INSERT INTO document_relations (source_document_id, target_document_id, relation_type, note, created_by)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (source_document_id, target_document_id, relation_type) DO NOTHING
+               RETURNING {}"#,
+            DOCUMENT_RELATION_COLUMNS
+        );
+        let relation = sqlx::query_as::<_, DocumentRelation>(&query)
+            .bind(source_document_id)
+            .bind(request.target_document_id)
+            .bind(request.relation_type.to_string())
+            .bind(request.note.as_deref().map(str::trim).filter(|note| !note.is_empty()))
+            .bind(created_by)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(relation)
+    }
+
+    /// The relation, when the document is one of its ends
+    pub async fn get_document_relation(&self, document_id: Uuid, id: Uuid) -> Result<Option<DocumentRelation>> {
+        let query = format!(
+            "SELECT {} FROM document_relations WHERE id = $1 AND (source_document_id = $2 OR target_document_id = $2)",
+            DOCUMENT_RELATION_COLUMNS
+        );
+        let relation = sqlx::query_as::<_, DocumentRelation>(&query)
+            .bind(id)
+            .bind(document_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(relation)
+    }
+
+    /// None when the new type makes it a duplicate of another relation
+    pub async fn update_document_relation(
+        &self,
+        id: Uuid,
+        request: &UpdateDocumentRelationRequest,
+    ) -> Result<Option<DocumentRelation>> {
+        let query = format!(
+            r#"UPDATE document_relations r
+               SET relation_type = COALESCE($2, r.relation_type),
+                   note = CASE WHEN $3::text IS NULL THEN r.note ELSE NULLIF(btrim($3), '') END,
+                   updated_at = NOW()
+               WHERE r.id = $1
+                 AND NOT EXISTS (
+                     SELECT 1 FROM document_relations o
+                     WHERE o.id <> r.id AND o.source_document_id = r.source_document_id
+                       AND o.target_document_id = r.target_document_id
+                       AND o.relation_type = COALESCE($2, r.relation_type)
+                 )
+               RETURNING {}"#,
+            DOCUMENT_RELATION_COLUMNS
+        );
+        let relation = sqlx::query_as::<_, DocumentRelation>(&query)
+            .bind(id)
+            .bind(request.relation_type.map(|relation_type| relation_type.to_string()))
+            .bind(request.note.as_deref())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(relation)
+    }
+
+    pub async fn delete_document_relation(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM document_relations WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Relations of the document to documents the user can see, newest first
+    pub async fn get_linked_document_relations(
+        &self,
+        document_id: Uuid,
+        user_id: Uuid,
+        user_role: UserRole,
+    ) -> Result<Vec<LinkedDocumentRelation>> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT r.id, r.source_document_id, r.target_document_id, r.relation_type, r.note, r.created_by, \
+             r.created_at, r.updated_at, \
+             CASE WHEN r.source_document_id = ",
+        );
+        query.push_bind(document_id);
+        query.push(
+            " THEN 'outgoing' ELSE 'incoming' END AS direction, \
+             d.id AS document_id, d.original_filename AS filename \
+             FROM document_relations r \
+             JOIN documents d ON d.id = CASE WHEN r.source_document_id = ",
+        );
+        query.push_bind(document_id);
+        query.push(" THEN r.target_document_id ELSE r.source_document_id END WHERE (r.source_document_id = ");
+        query.push_bind(document_id);
+        query.push(" OR r.target_document_id = ");
+        query.push_bind(document_id);
+        query.push(") AND d.id IN (SELECT id FROM documents WHERE 1=1");
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::View);
+        query.push(") ORDER BY r.created_at DESC, r.id");
+
+        let relations = query.build_query_as::<LinkedDocumentRelation>().fetch_all(&self.pool).await?;
+        Ok(relations)
+    }
+
+    /// Relations to documents of the same owner whose latest knowledge graph names at
+    /// least `min_shared` of the entities this document's does, most shared first.
+    /// Documents already related to it are left out.
+    pub async fn get_document_relation_suggestions(
+        &self,
+        document_id: Uuid,
+        min_shared: i64,
+        limit: i64,
+    ) -> Result<Vec<DocumentRelationSuggestion>> {
+        let query = format!(
+            r#"WITH mine AS (
+                   SELECT DISTINCT n.label, lower(btrim(n.name)) AS name FROM {latest} AND n.document_id = $1
+               ),
+               shared AS (
+                   SELECT n.document_id,
+                          ARRAY_AGG(DISTINCT n.name) AS shared_entities,
+                          COUNT(DISTINCT (n.label, lower(btrim(n.name)))) AS shared_count,
+                          ARRAY_AGG(n.id) AS node_ids
+                   FROM {latest} AND n.document_id <> $1
+                     AND (n.label, lower(btrim(n.name))) IN (SELECT label, name FROM mine)
+                   GROUP BY n.document_id
+               )
+               SELECT s.document_id, d.original_filename AS filename, s.shared_entities,
+                      ARRAY(SELECT DISTINCT e.relationship FROM document_edges e
+                            WHERE e.source_node_id = ANY(s.node_ids) OR e.target_node_id = ANY(s.node_ids)) AS relationships
+               FROM shared s
+               JOIN documents d ON d.id = s.document_id
+               WHERE s.shared_count >= $2
+                 AND d.user_id = (SELECT user_id FROM documents WHERE id = $1)
+                 AND NOT EXISTS (
+                     SELECT 1 FROM document_relations r
+                     WHERE (r.source_document_id = $1 AND r.target_document_id = s.document_id)
+                        OR (r.source_document_id = s.document_id AND r.target_document_id = $1)
+                 )
+               ORDER BY s.shared_count DESC, d.created_at DESC
+               LIMIT $3"#,
+            latest = LATEST_NODES
+        );
+        let documents = sqlx::query_as::<_, GraphRelatedDocument>(&query)
+            .bind(document_id)
+            .bind(min_shared)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(documents
+            .into_iter()
+            .map(|related| {
+                DocumentRelationSuggestion::new(
+                    document_id,
+                    related.document_id,
+                    related.filename,
+                    related.shared_entities,
+                    related.relationships,
+                )
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{DocumentSplit, SplitMethod};
+
+impl Database {
+    pub async fn create_document_split(
+        &self,
+        parent_document_id: Uuid,
+        child_document_id: Uuid,
+        start_page: i32,
+        end_page: i32,
+        method: SplitMethod,
+    ) -> Result<DocumentSplit> {
+        let split = sqlx::query_as::<_, DocumentSplit>(
+            r#"INSERT INTO document_splits (parent_document_id, child_document_id, start_page, end_page, method)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING id, parent_document_id, child_document_id, start_page, end_page, method, created_at"#
+        )
+        .bind(parent_document_id)
+        .bind(child_document_id)
+        .bind(start_page)
+        .bind(end_page)
+        .bind(method.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(split)
+    }
+
+    pub async fn get_document_splits(&self, parent_document_id: Uuid) -> Result<Vec<DocumentSplit>> {
+        let splits = sqlx::query_as::<_, DocumentSplit>(
+            r#"SELECT id, parent_document_id, child_document_id, start_page, end_page, method, created_at
+               FROM document_splits
+               WHERE parent_document_id = $1
+               ORDER BY start_page"#
+        )
+        .bind(parent_document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(splits)
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::DocumentTitle;
+
+impl Database {
+    pub async fn get_document_title(&self, document_id: Uuid) -> Result<Option<DocumentTitle>> {
+        let title = sqlx::query_as::<_, DocumentTitle>(
+            "SELECT document_id, original_filename, title, generated_by, created_at FROM document_titles WHERE document_id = $1",
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(title)
+    }
+
+    /// Record a generated title. A document titled again keeps the name it had before
+    /// its first title.
+    pub async fn upsert_document_title(
+        &self,
+        document_id: Uuid,
+        original_filename: &str,
+        title: &str,
+        generated_by: &str,
+    ) -> Result<DocumentTitle> {
+        let title = sqlx::query_as::<_, DocumentTitle>(
+            r#"INSERT INTO document_titles (document_id, original_filename, title, generated_by)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (document_id) DO UPDATE
+               SET title = EXCLUDED.title, generated_by = EXCLUDED.generated_by, created_at = NOW()
+               RETURNING document_id, original_filename, title, generated_by, created_at"#,
+        )
+        .bind(document_id)
+        .bind(original_filename)
+        .bind(title)
+        .bind(generated_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(title)
+    }
+
+    pub async fn delete_document_title(&self, document_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM document_titles WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,223 @@
+use anyhow::Result;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{CustomFieldDefinition, DocumentCustomFields, DocumentType};
+
+const DOCUMENT_TYPE_COLUMNS: &str = "id, user_id, name, fields, created_at, updated_at";
+
+impl Database {
+    pub async fn create_document_type(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        fields: &[CustomFieldDefinition],
+    ) -> Result<DocumentType> {
+        let query = format!(
+            "INSERT INTO document_types (user_id, name, fields) VALUES ($1, $2, $3) RETURNING {}",
+            DOCUMENT_TYPE_COLUMNS
+        );
+        let document_type = sqlx::query_as::<_, DocumentType>(&query)
+            .bind(user_id)
+            .bind(name)
+            .bind(sqlx::types::Json(fields))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(document_type)
+    }
+
+    /// The user's document types by name
+    pub async fn get_document_types(&self, user_id: Uuid) -> Result<Vec<DocumentType>> {
+        let query = format!(
+            "SELECT {} FROM document_types WHERE user_id = $1 ORDER BY LOWER(name)",
+            DOCUMENT_TYPE_COLUMNS
+        );
+        let document_types = sqlx::query_as::<_, DocumentType>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(document_types)
+    }
+
+    pub async fn get_document_type(&self, id: Uuid, user_id: Uuid) -> Result<Option<DocumentType>> {
+        let query = format!(
+            "SELECT {} FROM document_types WHERE id = $1 AND user_id = $2",
+            DOCUMENT_TYPE_COLUMNS
+        );
+        let document_type = sqlx::query_as::<_, DocumentType>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(document_type)
+    }
+
+    /// The user's document type with this name, created with the fields if it does not
+    /// exist. An existing type gets the fields it lacks appended.
+    pub async fn ensure_document_type(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        fields: &[CustomFieldDefinition],
+    ) -> Result<DocumentType> {
+        let query = format!(
+            r#"INSERT INTO document_types (user_id, name, fields) VALUES ($1, $2, $3)
+               ON CONFLICT (user_id, (LOWER(name))) DO UPDATE SET name = document_types.name
+               RETURNING {}"#,
+            DOCUMENT_TYPE_COLUMNS
+        );
+        let document_type = sqlx::query_as::<_, DocumentType>(&query)
+            .bind(user_id)
+            .bind(name)
+            .bind(sqlx::types::Json(fields))
+            .fetch_one(&self.pool)
+            .await?;
+
+        let missing: Vec<&CustomFieldDefinition> = fields
+            .iter()
+            .filter(|field| !document_type.fields.iter().any(|existing| existing.name == field.name))
+            .collect();
+        if missing.is_empty() {
+            return Ok(document_type);
+        }
+
+        let mut merged = document_type.fields.0.clone();
+        merged.extend(missing.into_iter().cloned());
+        Ok(self
+            .update_document_type(document_type.id, user_id, None, Some(&merged))
+            .await?
+            .unwrap_or(document_type))
+    }
+
+    /// Whether another of the user's document types has this name, ignoring case
+    pub async fn document_type_name_taken(&self, user_id: Uuid, name: &str, except: Option<Uuid>) -> Result<bool> {
+        let taken = sqlx::query_scalar::<_, bool>(
+            r#"SELECT EXISTS(
+                   SELECT 1 FROM document_types
+                   WHERE user_id = $1 AND LOWER(name) = LOWER($2) AND ($3::uuid IS NULL OR id <> $3)
+               )"#,
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(except)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(taken)
+    }
+
+    /// Renames a document type or replaces its fields. Values already stored on
+    /// documents are kept.
+    pub async fn update_document_type(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        name: Option<&str>,
+        fields: Option<&[CustomFieldDefinition]>,
+    ) -> Result<Option<DocumentType>> {
+        let query = format!(
+            r#"UPDATE document_types
+               SET name = COALESCE($3, name),
+                   fields = COALESCE($4, fields),
+                   updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            DOCUMENT_TYPE_COLUMNS
+        );
+        let document_type = sqlx::query_as::<_, DocumentType>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(name)
+            .bind(fields.map(sqlx::types::Json))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(document_type)
+    }
+
+    /// Deletes a document type along with the custom field values of its documents
+    pub async fn delete_document_type(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM document_types WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_document_custom_fields(&self, document_id: Uuid) -> Result<Option<DocumentCustomFields>> {
+        let custom_fields = sqlx::query_as::<_, DocumentCustomFields>(
+            r#"SELECT dcf.document_id, dcf.document_type_id, dt.name AS document_type_name, dcf.fields, dcf.updated_at
+               FROM document_custom_fields dcf
+               JOIN document_types dt ON dt.id = dcf.document_type_id
+               WHERE dcf.document_id = $1"#,
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(custom_fields)
+    }
+
+    /// Sets a document's type and custom field values, which must already be validated
+    pub async fn set_document_custom_fields(
+        &self,
+        document_id: Uuid,
+        document_type_id: Uuid,
+        fields: &Map<String, Value>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO document_custom_fields (document_id, document_type_id, fields)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (document_id) DO UPDATE
+               SET document_type_id = EXCLUDED.document_type_id, fields = EXCLUDED.fields, updated_at = NOW()"#,
+        )
+        .bind(document_id)
+        .bind(document_type_id)
+        .bind(sqlx::types::Json(fields))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Filename and custom field values of each of the user's documents of the type,
+    /// oldest first
+    pub async fn get_documents_of_type(
+        &self,
+        document_type_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<(Uuid, String, Map<String, Value>)>> {
+        let rows = sqlx::query_as::<_, (Uuid, String, sqlx::types::Json<Map<String, Value>>)>(
+            r#"SELECT d.id, d.original_filename, dcf.fields
+               FROM document_custom_fields dcf
+               JOIN documents d ON d.id = dcf.document_id
+               WHERE dcf.document_type_id = $1 AND d.user_id = $2
+               ORDER BY d.created_at"#,
+        )
+        .bind(document_type_id)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(document_id, filename, fields)| (document_id, filename, fields.0))
+            .collect())
+    }
+
+    /// Removes a document's type and custom field values
+    pub async fn clear_document_custom_fields(&self, document_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM document_custom_fields WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::documents::{map_row_to_document, replace_document_file_in, DOCUMENT_FIELDS};
+use super::Database;
+use crate::models::{Document, DocumentVersion};
+
+/// New contents for a document whose current contents become a version
+pub struct NewDocumentContents<'a> {
+    pub file_path: &'a str,
+    pub file_size: i64,
+    pub file_hash: &'a str,
+    pub original_modified_at: Option<DateTime<Utc>>,
+}
+
+impl Database {
+    /// The document a file at `source_path` was ingested as by the same source
+    pub async fn find_document_by_source_path(
+        &self,
+        user_id: Uuid,
+        source_type: Option<&str>,
+        source_id: Option<Uuid>,
+        source_path: &str,
+    ) -> Result<Option<Document>> {
+        let query = format!(
+            r#"SELECT {} FROM documents
+               WHERE user_id = $1 AND source_path = $4
+                 AND source_type IS NOT DISTINCT FROM $2 AND source_id IS NOT DISTINCT FROM $3
+               ORDER BY created_at
+               LIMIT 1"#,
+            DOCUMENT_FIELDS
+        );
+        let row = sqlx::query(&query)
+            .bind(user_id)
+            .bind(source_type)
+            .bind(source_id)
+            .bind(source_path)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(map_row_to_document))
+    }
+
+    /// Keep the document's current contents as its next version, stored at
+    /// `version_file_path`, and point the document at new contents. OCR results are
+    /// reset so the new contents are processed again.
+    pub async fn store_document_version(
+        &self,
+        document_id: Uuid,
+        version_id: Uuid,
+        version_file_path: &str,
+        contents: &NewDocumentContents<'_>,
+    ) -> Result<(DocumentVersion, Document)> {
+        let mut tx = self.pool.begin().await?;
+
+        // Serializes concurrent version changes of the same document
+        sqlx::query("SELECT id FROM documents WHERE id = $1 FOR UPDATE")
+            .bind(document_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let version = sqlx::query_as::<_, DocumentVersion>(
+            r#"INSERT INTO document_versions (
+                   id, document_id, version_number, filename, file_path, file_size, file_hash, mime_type,
+                   ocr_text, ocr_confidence, ocr_word_count, original_modified_at, created_at
+               )
+               SELECT $2, d.id,
+                      COALESCE((SELECT MAX(version_number) FROM document_versions WHERE document_id = d.id), 0) + 1,
+                      d.filename, $3, d.file_size, d.file_hash, d.mime_type,
+                      d.ocr_text, d.ocr_confidence, d.ocr_word_count, d.original_modified_at,
+                      COALESCE((SELECT MAX(superseded_at) FROM document_versions WHERE document_id = d.id), d.created_at)
+               FROM documents d
+               WHERE d.id = $1
+               RETURNING *"#
+        )
+        .bind(document_id)
+        .bind(version_id)
+        .bind(version_file_path)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE documents SET original_modified_at = $2 WHERE id = $1")
+            .bind(document_id)
+            .bind(contents.original_modified_at)
+            .execute(&mut *tx)
+            .await?;
+        let document = replace_document_file_in(
+            &mut tx,
+            document_id,
+            contents.file_path,
+            contents.file_size,
+            contents.file_hash,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok((version, document))
+    }
+
+    /// Earlier versions of a document, newest first
+    pub async fn get_document_versions(&self, document_id: Uuid) -> Result<Vec<DocumentVersion>> {
+        let versions = sqlx::query_as::<_, DocumentVersion>(
+            "SELECT * FROM document_versions WHERE document_id = $1 ORDER BY version_number DESC"
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(versions)
+    }
+
+    /// Hides every earlier version of the document from all but its owner and admins,
+    /// after the current contents were redacted
+    pub async fn restrict_document_versions(&self, document_id: Uuid) -> Result<u64> {
+        let result = sqlx::query("UPDATE document_versions SET restricted = TRUE WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_document_version(&self, document_id: Uuid, version_number: i32) -> Result<Option<DocumentVersion>> {
+        let version = sqlx::query_as::<_, DocumentVersion>(
+            "SELECT * FROM document_versions WHERE document_id = $1 AND version_number = $2"
+        )
+        .bind(document_id)
+        .bind(version_number)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(version)
+    }
+
+    /// Storage ids of every kept version, whose files are named after them
+    pub async fn get_document_version_ids(&self) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar::<_, Uuid>("SELECT id FROM document_versions")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(ids)
+    }
+}
@@ -1,9 +1,10 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use sqlx::{QueryBuilder, Postgres};
 use uuid::Uuid;
 
-use crate::models::{Document, UserRole};
-use super::helpers::{map_row_to_document, apply_role_based_filter, apply_pagination, DOCUMENT_FIELDS};
+use crate::models::{Document, SharePermission, UserRole};
+use super::helpers::{map_row_to_document, apply_role_based_filter, apply_shared_access_filter, apply_pagination, DOCUMENT_FIELDS};
 use crate::db::Database;
 
 impl Database {
@@ -55,6 +56,66 @@ impl Database {
         Ok(map_row_to_document(&row))
     }
 
+    /// Inserts documents in a single statement. Rows whose hash the user already has are
+    /// skipped; returns the ids that were inserted.
+    pub async fn insert_documents_batch(&self, documents: &[Document]) -> Result<Vec<Uuid>> {
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut query = QueryBuilder::<Postgres>::new(
+            "INSERT INTO documents (id, filename, original_filename, file_path, file_size, mime_type, ocr_status, tags, created_at, updated_at, user_id, file_hash, original_created_at, original_modified_at, source_path, source_type, source_id, file_permissions, file_owner, file_group, source_metadata) "
+        );
+        query.push_values(documents, |mut row, document| {
+            row.push_bind(document.id)
+                .push_bind(&document.filename)
+                .push_bind(&document.original_filename)
+                .push_bind(&document.file_path)
+                .push_bind(document.file_size)
+                .push_bind(&document.mime_type)
+                .push_bind(&document.ocr_status)
+                .push_bind(&document.tags)
+                .push_bind(document.created_at)
+                .push_bind(document.updated_at)
+                .push_bind(document.user_id)
+                .push_bind(&document.file_hash)
+                .push_bind(document.original_created_at)
+                .push_bind(document.original_modified_at)
+                .push_bind(&document.source_path)
+                .push_bind(&document.source_type)
+                .push_bind(document.source_id)
+                .push_bind(document.file_permissions)
+                .push_bind(&document.file_owner)
+                .push_bind(&document.file_group)
+                .push_bind(&document.source_metadata);
+        });
+        query.push(" ON CONFLICT (user_id, file_hash) WHERE file_hash IS NOT NULL DO NOTHING RETURNING id");
+
+        let ids = query
+            .build_query_scalar::<Uuid>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(ids)
+    }
+
+    /// Which of these hashes the user already has a document for
+    pub async fn get_existing_file_hashes(&self, user_id: Uuid, file_hashes: &[String]) -> Result<Vec<String>> {
+        if file_hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let hashes = sqlx::query_scalar::<_, String>(
+            "SELECT file_hash FROM documents WHERE user_id = $1 AND file_hash = ANY($2)"
+        )
+        .bind(user_id)
+        .bind(file_hashes)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(hashes)
+    }
+
     /// Retrieves a document by ID with role-based access control
     pub async fn get_document_by_id(&self, document_id: Uuid, user_id: Uuid, user_role: UserRole) -> Result<Option<Document>> {
         let mut query = QueryBuilder::<Postgres>::new("SELECT ");
@@ -72,6 +133,29 @@ impl Database {
         Ok(row.map(|r| map_row_to_document(&r)))
     }
 
+    /// Retrieves a document the user owns, or that is shared with them with at least `permission`
+    pub async fn get_shared_document_by_id(
+        &self,
+        document_id: Uuid,
+        user_id: Uuid,
+        user_role: UserRole,
+        permission: SharePermission,
+    ) -> Result<Option<Document>> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT ");
+        query.push(DOCUMENT_FIELDS);
+        query.push(" FROM documents WHERE id = ");
+        query.push_bind(document_id);
+
+        apply_shared_access_filter(&mut query, user_id, user_role, permission);
+
+        let row = query
+            .build()
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|r| map_row_to_document(&r)))
+    }
+
     /// Gets documents for a user with role-based access and pagination
     pub async fn get_documents_by_user(&self, user_id: Uuid, limit: i64, offset: i64) -> Result<Vec<Document>> {
         let query_str = format!(
@@ -179,6 +263,113 @@ impl Database {
         Ok(())
     }
 
+    /// Backdates a document's creation time, e.g. to when another system first stored it
+    pub async fn set_document_created_at(&self, document_id: Uuid, created_at: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        sqlx::query("UPDATE documents SET created_at = $2 WHERE id = $1")
+            .bind(document_id)
+            .bind(created_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Appends text to a document's OCR text so it becomes searchable
+    pub async fn append_document_ocr_text(&self, document_id: Uuid, text: &str) -> Result<()> {
+        let word_count = text.split_whitespace().count() as i32;
+
+        sqlx::query(
+            r#"
+            UPDATE documents
+            SET ocr_text = CASE
+                    WHEN COALESCE(ocr_text, '') = '' THEN $2
+                    ELSE ocr_text || E'\n\n' || $2
+                END,
+                ocr_word_count = COALESCE(ocr_word_count, 0) + $3,
+                updated_at = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(document_id)
+        .bind(text)
+        .bind(word_count)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replaces a document's OCR text with a correction, provided it is still
+    /// `expected`. Returns when the correction was made, or None when the text changed
+    /// in the meantime.
+    pub async fn correct_document_ocr_text(
+        &self,
+        document_id: Uuid,
+        expected: Option<&str>,
+        text: &str,
+        corrected_by: Uuid,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let word_count = text.split_whitespace().count() as i32;
+
+        let corrected_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            UPDATE documents
+            SET ocr_text = $3,
+                ocr_word_count = $4,
+                ocr_text_corrected_at = NOW(),
+                ocr_text_corrected_by = $5,
+                updated_at = NOW()
+            WHERE id = $1 AND ocr_text IS NOT DISTINCT FROM $2
+            RETURNING ocr_text_corrected_at
+            "#
+        )
+        .bind(document_id)
+        .bind(expected)
+        .bind(text)
+        .bind(word_count)
+        .bind(corrected_by)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(corrected_at)
+    }
+
+    /// When and by whom the document's OCR text was corrected, if it still is
+    pub async fn get_ocr_text_correction(&self, document_id: Uuid) -> Result<Option<(DateTime<Utc>, Option<Uuid>)>> {
+        let correction = sqlx::query_as::<_, (DateTime<Utc>, Option<Uuid>)>(
+            r#"SELECT ocr_text_corrected_at, ocr_text_corrected_by FROM documents
+               WHERE id = $1 AND ocr_text_corrected_at IS NOT NULL"#
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(correction)
+    }
+
+    /// Keep the OCR text a document's `ocr_text` was normalized from, provided its text
+    /// is still `normalized`
+    pub async fn set_document_ocr_text_raw(&self, document_id: Uuid, normalized: &str, raw: &str) -> Result<()> {
+        sqlx::query("UPDATE documents SET ocr_text_raw = $3 WHERE id = $1 AND ocr_text = $2")
+            .bind(document_id)
+            .bind(normalized)
+            .bind(raw)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The OCR text as the OCR engine returned it, if normalization changed it
+    pub async fn get_document_ocr_text_raw(&self, document_id: Uuid) -> Result<Option<String>> {
+        let raw = sqlx::query_scalar::<_, Option<String>>("SELECT ocr_text_raw FROM documents WHERE id = $1")
+            .bind(document_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(raw.flatten())
+    }
+
     /// Gets recent documents for a specific source
     pub async fn get_recent_documents_for_source(&self, user_id: Uuid, source_id: Uuid, limit: i64) -> Result<Vec<Document>> {
         let query_str = format!(
@@ -1,7 +1,8 @@
 use sqlx::{Row, QueryBuilder, Postgres};
 use uuid::Uuid;
 
-use crate::models::{Document, UserRole};
+use crate::models::{Document, SharePermission, UserRole, WorkspaceRole};
+use crate::services::workspace_service;
 
 /// Standard document fields for SELECT queries
 pub const DOCUMENT_FIELDS: &str = r#"
@@ -13,6 +14,10 @@ pub const DOCUMENT_FIELDS: &str = r#"
     file_owner, file_group, source_metadata
 "#;
 
+/// Text of a document as covered by `idx_documents_content_trgm`; filtering on it lets
+/// Postgres use the trigram index instead of reading every document
+pub const INDEXED_TEXT: &str = "(COALESCE(content, '') || ' ' || COALESCE(ocr_text, ''))";
+
 /// Maps a database row to a Document struct
 /// This eliminates the ~15+ instances of duplicate row mapping code
 pub fn map_row_to_document(row: &sqlx::postgres::PgRow) -> Document {
@@ -51,7 +56,8 @@ pub fn map_row_to_document(row: &sqlx::postgres::PgRow) -> Document {
 }
 
 /// Applies role-based filtering to a query builder
-/// Admins can see all documents, regular users only see their own
+/// Admins can see all documents, regular users only see their own and, in multi-tenant
+/// mode, those of their workspaces that are not embargoed
 pub fn apply_role_based_filter(
     query: &mut QueryBuilder<Postgres>, 
     user_id: Uuid, 
@@ -62,12 +68,88 @@ pub fn apply_role_based_filter(
             // Admins can see all documents - no additional filter needed
         }
         UserRole::User => {
-            query.push(" AND user_id = ");
+            query.push(" AND (user_id = ");
+            query.push_bind(user_id);
+            if workspace_service::enabled() {
+                query.push(" OR (NOT ");
+                query.push(embargoed_condition("documents.id"));
+                query.push(" AND (FALSE");
+                push_workspace_access(query, user_id, WorkspaceRole::Viewer);
+                query.push("))");
+            }
+            query.push(")");
+        }
+    }
+}
+
+/// Like [`apply_role_based_filter`], also letting regular users through to documents
+/// shared with them with at least `permission` that are not embargoed
+pub fn apply_shared_access_filter(
+    query: &mut QueryBuilder<Postgres>,
+    user_id: Uuid,
+    role: UserRole,
+    permission: SharePermission,
+) {
+    match role {
+        UserRole::Admin => {}
+        UserRole::User => {
+            query.push(" AND (user_id = ");
+            query.push_bind(user_id);
+            query.push(" OR (NOT ");
+            query.push(embargoed_condition("documents.id"));
+            query.push(" AND (id IN (SELECT document_id FROM shared_document_access WHERE user_id = ");
             query.push_bind(user_id);
+            query.push(" AND permission >= ");
+            query.push_bind(permission);
+            query.push(")");
+            push_workspace_access(query, user_id, workspace_role_for(permission));
+            query.push(")))");
         }
     }
 }
 
+/// The workspace role that grants what a share with `permission` would
+fn workspace_role_for(permission: SharePermission) -> WorkspaceRole {
+    match permission {
+        SharePermission::View => WorkspaceRole::Viewer,
+        SharePermission::Edit | SharePermission::Delete => WorkspaceRole::Editor,
+    }
+}
+
+/// In multi-tenant mode, also lets members of a document's workspace through when their
+/// role there is at least `role`. Pushed inside the parentheses opened by the filters above.
+fn push_workspace_access(query: &mut QueryBuilder<Postgres>, user_id: Uuid, role: WorkspaceRole) {
+    if !workspace_service::enabled() {
+        return;
+    }
+    query.push(" OR workspace_id IN (SELECT workspace_id FROM workspace_members WHERE user_id = ");
+    query.push_bind(user_id);
+    query.push(" AND role >= ");
+    query.push_bind(role);
+    query.push(")");
+}
+
+/// Leaves out documents on an active legal hold, which may not be deleted or changed.
+/// The database refuses such changes anyway; filtering first keeps the refusal from
+/// aborting the transaction the statement runs in.
+pub fn exclude_legal_holds(query: &mut QueryBuilder<Postgres>) {
+    query.push(
+        " AND NOT EXISTS (SELECT 1 FROM legal_hold_documents lhd JOIN legal_holds lh ON lh.id = lhd.hold_id \
+         WHERE lhd.document_id = documents.id AND lh.released_at IS NULL)",
+    );
+}
+
+/// SQL condition that holds while the document in `document_column` is under an active
+/// embargo. Only admins and the owner see such a document: shares, workspaces, share
+/// links and guest portals do not reach it until the embargo ends.
+pub fn embargoed_condition(document_column: &str) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM document_embargoes de WHERE de.document_id = {} \
+         AND de.released_at IS NULL AND (de.embargoed_until IS NULL OR de.embargoed_until > NOW()))",
+        document_column
+    )
+}
+
 /// Applies pagination to a query builder
 pub fn apply_pagination(query: &mut QueryBuilder<Postgres>, limit: i64, offset: i64) {
     query.push(" LIMIT ");
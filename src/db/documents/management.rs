@@ -1,9 +1,11 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use sqlx::{QueryBuilder, Postgres, Row};
 use uuid::Uuid;
 
-use crate::models::{Document, UserRole, FacetItem};
+use crate::models::{BatchUploadManifestEntry, Document, UserRole, FacetItem};
 use crate::routes::labels::Label;
+use crate::utils::pagination::PageRequest;
 use super::helpers::{map_row_to_document, apply_role_based_filter, DOCUMENT_FIELDS};
 use crate::db::Database;
 
@@ -13,7 +15,7 @@ impl Database {
         let rows = sqlx::query_as::<_, Label>(
             r#"
             SELECT
-                l.id, l.user_id, l.name, l.description, l.color,
+                l.id, l.user_id, l.parent_id, l.name, l.description, l.color,
                 l.background_color, l.icon, l.is_system, l.created_at, l.updated_at,
                 0::bigint as document_count, 0::bigint as source_count
             FROM labels l
@@ -29,6 +31,169 @@ impl Database {
         Ok(rows)
     }
 
+    /// Assigns the user's label with this name to a document, creating the label if needed
+    pub async fn assign_label_by_name(&self, user_id: Uuid, document_id: Uuid, label_name: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let label_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO labels (user_id, name)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id, name) DO UPDATE SET name = EXCLUDED.name
+            RETURNING id
+            "#
+        )
+        .bind(user_id)
+        .bind(label_name)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO document_labels (document_id, label_id, assigned_by) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"
+        )
+        .bind(document_id)
+        .bind(label_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Give a new document the labels, correspondent and date of its entry in a batch
+    /// upload manifest, all or nothing. Labels and the correspondent are created by name
+    /// when the user does not have them yet.
+    pub async fn apply_batch_upload_entry(
+        &self,
+        user_id: Uuid,
+        document_id: Uuid,
+        entry: &BatchUploadManifestEntry,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut label_ids = entry.label_ids.clone();
+        for name in &entry.labels {
+            let label_id: Uuid = sqlx::query_scalar(
+                r#"
+                INSERT INTO labels (user_id, name)
+                VALUES ($1, $2)
+                ON CONFLICT (user_id, name) DO UPDATE SET name = EXCLUDED.name
+                RETURNING id
+                "#
+            )
+            .bind(user_id)
+            .bind(name.trim())
+            .fetch_one(&mut *tx)
+            .await?;
+            label_ids.push(label_id);
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO document_labels (document_id, label_id, assigned_by)
+            SELECT $1, label_id, $3 FROM UNNEST($2::uuid[]) AS label_id
+            ON CONFLICT DO NOTHING
+            "#
+        )
+        .bind(document_id)
+        .bind(&label_ids)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if let Some(name) = &entry.correspondent {
+            let correspondent_id: Uuid = sqlx::query_scalar(
+                r#"
+                INSERT INTO correspondents (user_id, name)
+                VALUES ($1, $2)
+                ON CONFLICT (user_id, (LOWER(name))) DO UPDATE SET name = correspondents.name
+                RETURNING id
+                "#
+            )
+            .bind(user_id)
+            .bind(name.trim())
+            .fetch_one(&mut *tx)
+            .await?;
+            sqlx::query(
+                r#"
+                INSERT INTO document_correspondents (document_id, correspondent_id, assigned_by)
+                VALUES ($1, $2, 'manual')
+                ON CONFLICT (document_id) DO UPDATE
+                SET correspondent_id = EXCLUDED.correspondent_id, assigned_by = 'manual', assigned_at = NOW()
+                "#
+            )
+            .bind(document_id)
+            .bind(correspondent_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if let Some(date) = entry.document_date {
+            sqlx::query("UPDATE documents SET original_created_at = $2, updated_at = NOW() WHERE id = $1")
+                .bind(document_id)
+                .bind(date.and_hms_opt(0, 0, 0).map(|date| date.and_utc()))
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Id of the user's label with this name, created with `color` if it does not exist.
+    /// An existing label keeps its color.
+    pub async fn ensure_label(&self, user_id: Uuid, label_name: &str, color: Option<&str>) -> Result<Uuid> {
+        let label_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO labels (user_id, name, color)
+            VALUES ($1, $2, COALESCE($3, '#0969da'))
+            ON CONFLICT (user_id, name) DO UPDATE SET name = EXCLUDED.name
+            RETURNING id
+            "#
+        )
+        .bind(user_id)
+        .bind(label_name)
+        .bind(color)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(label_id)
+    }
+
+    pub async fn assign_label(&self, document_id: Uuid, label_id: Uuid, assigned_by: Uuid) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO document_labels (document_id, label_id, assigned_by) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"
+        )
+        .bind(document_id)
+        .bind(label_id)
+        .bind(assigned_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Labels the user can use: their own, the system labels and those of the workspace
+    pub async fn get_visible_labels(&self, user_id: Uuid, workspace_id: Option<Uuid>) -> Result<Vec<Label>> {
+        let rows = sqlx::query_as::<_, Label>(
+            r#"
+            SELECT
+                id, user_id, parent_id, name, description, color,
+                background_color, icon, is_system, created_at, updated_at,
+                0::bigint as document_count, 0::bigint as source_count
+            FROM labels
+            WHERE (user_id = $1 OR is_system = TRUE OR workspace_id = $2)
+            ORDER BY name
+            "#
+        )
+        .bind(user_id)
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
     /// Gets labels for multiple documents in batch
     pub async fn get_labels_for_documents(&self, document_ids: &[Uuid]) -> Result<Vec<(Uuid, Vec<Label>)>> {
         if document_ids.is_empty() {
@@ -37,7 +202,7 @@ impl Database {
 
         let rows = sqlx::query(
             r#"
-            SELECT dl.document_id, l.id as label_id, l.user_id, l.name, l.color, l.created_at, l.updated_at
+            SELECT dl.document_id, l.id as label_id, l.user_id, l.parent_id, l.name, l.color, l.created_at, l.updated_at
             FROM labels l
             JOIN document_labels dl ON l.id = dl.label_id
             WHERE dl.document_id = ANY($1)
@@ -57,6 +222,7 @@ impl Database {
             let label = Label {
                 id: row.get("label_id"),
                 user_id: row.get("user_id"),
+                parent_id: row.get("parent_id"),
                 name: row.get("name"),
                 description: None,
                 color: row.get("color"),
@@ -151,14 +317,16 @@ impl Database {
 
     /// Gets MIME type facets (aggregated counts by MIME type)
     pub async fn get_mime_type_facets(&self, user_id: Uuid, user_role: UserRole) -> Result<Vec<FacetItem>> {
-        let mut query = QueryBuilder::<Postgres>::new(
-            "SELECT mime_type as value, COUNT(*) as count FROM documents WHERE 1=1"
-        );
+        let rows = self.read_with_fallback(|pool| {
+            let mut query = QueryBuilder::<Postgres>::new(
+                "SELECT mime_type as value, COUNT(*) as count FROM documents WHERE 1=1"
+            );
 
-        apply_role_based_filter(&mut query, user_id, user_role);
-        query.push(" GROUP BY mime_type ORDER BY count DESC, mime_type");
+            apply_role_based_filter(&mut query, user_id, user_role);
+            query.push(" GROUP BY mime_type ORDER BY count DESC, mime_type");
 
-        let rows = query.build().fetch_all(&self.pool).await?;
+            async move { query.build().fetch_all(&pool).await }
+        }).await?;
 
         Ok(rows.into_iter().map(|row| FacetItem {
             value: row.get("value"),
@@ -168,14 +336,16 @@ impl Database {
 
     /// Gets tag facets (aggregated counts by tag)
     pub async fn get_tag_facets(&self, user_id: Uuid, user_role: UserRole) -> Result<Vec<FacetItem>> {
-        let mut query = QueryBuilder::<Postgres>::new(
-            "SELECT unnest(tags) as value, COUNT(*) as count FROM documents WHERE 1=1"
-        );
+        let rows = self.read_with_fallback(|pool| {
+            let mut query = QueryBuilder::<Postgres>::new(
+                "SELECT unnest(tags) as value, COUNT(*) as count FROM documents WHERE 1=1"
+            );
 
-        apply_role_based_filter(&mut query, user_id, user_role);
-        query.push(" GROUP BY unnest(tags) ORDER BY count DESC, value");
+            apply_role_based_filter(&mut query, user_id, user_role);
+            query.push(" GROUP BY unnest(tags) ORDER BY count DESC, value");
 
-        let rows = query.build().fetch_all(&self.pool).await?;
+            async move { query.build().fetch_all(&pool).await }
+        }).await?;
 
         Ok(rows.into_iter().map(|row| FacetItem {
             value: row.get("value"),
@@ -248,23 +418,7 @@ impl Database {
 
         apply_role_based_filter(&mut query, user_id, user_role);
 
-        if let Some(status) = ocr_status {
-            match status {
-                "pending" => {
-                    query.push(" AND (ocr_status IS NULL OR ocr_status = 'pending')");
-                }
-                "completed" => {
-                    query.push(" AND ocr_status = 'completed'");
-                }
-                "failed" => {
-                    query.push(" AND ocr_status = 'failed'");
-                }
-                _ => {
-                    query.push(" AND ocr_status = ");
-                    query.push_bind(status);
-                }
-            }
-        }
+        push_ocr_status_filter(&mut query, ocr_status);
 
         query.push(" ORDER BY created_at DESC");
         query.push(" LIMIT ");
@@ -276,6 +430,41 @@ impl Database {
         Ok(rows.iter().map(map_row_to_document).collect())
     }
 
+    /// A page of documents with role-based access and OCR status filtering, newest
+    /// first. Fetches `page.fetch_limit()` rows, starting after the page's cursor or
+    /// offset.
+    pub async fn get_documents_page(
+        &self,
+        user_id: Uuid,
+        user_role: UserRole,
+        ocr_status: Option<&str>,
+        page: &PageRequest<DateTime<Utc>>,
+    ) -> Result<Vec<Document>> {
+        let rows = self.read_with_fallback(|pool| {
+            let mut query = QueryBuilder::<Postgres>::new("SELECT ");
+            query.push(DOCUMENT_FIELDS);
+            query.push(" FROM documents WHERE 1=1");
+
+            apply_role_based_filter(&mut query, user_id, user_role);
+            push_ocr_status_filter(&mut query, ocr_status);
+            if let Some(after) = &page.after {
+                query.push(" AND (created_at, id) < (");
+                query.push_bind(after.key);
+                query.push(", ");
+                query.push_bind(after.id);
+                query.push(")");
+            }
+
+            query.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+            query.push_bind(page.fetch_limit());
+            query.push(" OFFSET ");
+            query.push_bind(page.offset);
+
+            async move { query.build().fetch_all(&pool).await }
+        }).await?;
+        Ok(rows.iter().map(map_row_to_document).collect())
+    }
+
     /// Counts documents with role-based access and OCR status filtering
     pub async fn get_documents_count_with_role_and_filter(
         &self, 
@@ -283,29 +472,29 @@ impl Database {
         user_role: UserRole, 
         ocr_status: Option<&str>
     ) -> Result<i64> {
-        let mut query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM documents WHERE 1=1");
+        let row = self.read_with_fallback(|pool| {
+            let mut query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM documents WHERE 1=1");
 
-        apply_role_based_filter(&mut query, user_id, user_role);
+            apply_role_based_filter(&mut query, user_id, user_role);
 
-        if let Some(status) = ocr_status {
-            match status {
-                "pending" => {
-                    query.push(" AND (ocr_status IS NULL OR ocr_status = 'pending')");
-                }
-                "completed" => {
-                    query.push(" AND ocr_status = 'completed'");
-                }
-                "failed" => {
-                    query.push(" AND ocr_status = 'failed'");
-                }
-                _ => {
-                    query.push(" AND ocr_status = ");
-                    query.push_bind(status);
-                }
-            }
-        }
+            push_ocr_status_filter(&mut query, ocr_status);
 
-        let row = query.build().fetch_one(&self.pool).await?;
+            async move { query.build().fetch_one(&pool).await }
+        }).await?;
         Ok(row.get(0))
     }
-}
\ No newline at end of file
+}
+
+/// Filter by OCR status; `pending` also matches documents that have none yet
+fn push_ocr_status_filter(query: &mut QueryBuilder<Postgres>, ocr_status: Option<&str>) {
+    match ocr_status {
+        Some("pending") => {
+            query.push(" AND (ocr_status IS NULL OR ocr_status = 'pending')");
+        }
+        Some(status) => {
+            query.push(" AND ocr_status = ");
+            query.push_bind(status.to_string());
+        }
+        None => {}
+    }
+}
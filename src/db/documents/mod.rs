@@ -7,4 +7,5 @@ mod management;
 mod operations;
 
 // Re-export helper functions for use by other modules if needed
-pub use helpers::*;
\ No newline at end of file
+pub use helpers::*;
+pub(crate) use operations::replace_document_file_in;
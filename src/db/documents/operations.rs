@@ -2,17 +2,19 @@ use anyhow::Result;
 use sqlx::{QueryBuilder, Postgres, Row};
 use uuid::Uuid;
 
-use crate::models::{Document, UserRole, FailedDocument};
-use super::helpers::{map_row_to_document, apply_role_based_filter, DOCUMENT_FIELDS};
+use crate::models::{Document, SharePermission, UserRole, FailedDocument};
+use super::helpers::{map_row_to_document, apply_role_based_filter, apply_shared_access_filter, exclude_legal_holds, DOCUMENT_FIELDS};
 use crate::db::Database;
 
 impl Database {
-    /// Deletes a single document with role-based access control
+    /// Deletes a single document with role-based access control; users it is shared
+    /// with for deletion may delete it too. Documents on legal hold are left alone.
     pub async fn delete_document(&self, document_id: Uuid, user_id: Uuid, user_role: UserRole) -> Result<bool> {
         let mut query = QueryBuilder::<Postgres>::new("DELETE FROM documents WHERE id = ");
         query.push_bind(document_id);
         
-        apply_role_based_filter(&mut query, user_id, user_role);
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::Delete);
+        exclude_legal_holds(&mut query);
 
         let result = query.build().execute(&self.pool).await?;
         Ok(result.rows_affected() > 0)
@@ -32,7 +34,8 @@ impl Database {
             let mut query = QueryBuilder::<Postgres>::new("DELETE FROM documents WHERE id = ");
             query.push_bind(doc_id);
             
-            apply_role_based_filter(&mut query, user_id, user_role);
+            apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::Delete);
+            exclude_legal_holds(&mut query);
             query.push(" RETURNING id");
 
             match query.build().fetch_optional(&mut *tx).await {
@@ -53,6 +56,28 @@ impl Database {
         Ok((deleted_ids, failed_ids))
     }
 
+    /// Changes a document's display name, and its source path when the file was
+    /// renamed on the source as well
+    pub async fn rename_document(&self, document_id: Uuid, original_filename: &str, source_path: Option<&str>) -> Result<Document> {
+        let query = format!(
+            r#"
+            UPDATE documents
+            SET original_filename = $2, source_path = COALESCE($3, source_path), updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            DOCUMENT_FIELDS
+        );
+        let row = sqlx::query(&query)
+            .bind(document_id)
+            .bind(original_filename)
+            .bind(source_path)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(map_row_to_document(&row))
+    }
+
     /// Finds documents with OCR confidence below threshold
     pub async fn find_documents_by_confidence_threshold(&self, user_id: Uuid, user_role: UserRole, max_confidence: f32, limit: i64, offset: i64) -> Result<Vec<Document>> {
         let mut query = QueryBuilder::<Postgres>::new("SELECT ");
@@ -227,6 +252,40 @@ impl Database {
         Ok(())
     }
 
+    /// Points a document at rewritten file content and resets its OCR state so
+    /// the new content is processed again. Page-level results are discarded.
+    pub async fn replace_document_file(
+        &self,
+        document_id: Uuid,
+        file_path: &str,
+        file_size: i64,
+        file_hash: &str,
+    ) -> Result<Document> {
+        let mut tx = self.pool.begin().await?;
+        let document = replace_document_file_in(&mut tx, document_id, file_path, file_size, file_hash).await?;
+        tx.commit().await?;
+        Ok(document)
+    }
+
+    /// Records a fact about a document, such as its signature report, under `key` of
+    /// its metadata, replacing what was there
+    pub async fn set_document_metadata_value(&self, document_id: Uuid, key: &str, value: serde_json::Value) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE documents
+            SET source_metadata = COALESCE(source_metadata, '{}'::jsonb) || jsonb_build_object($2::text, $3::jsonb)
+            WHERE id = $1
+            "#,
+        )
+        .bind(document_id)
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     /// Marks documents as completed OCR processing
     pub async fn mark_documents_ocr_completed(&self, document_ids: &[Uuid]) -> Result<u64> {
         if document_ids.is_empty() {
@@ -298,4 +357,58 @@ impl Database {
         let row = query.build().fetch_one(&self.pool).await?;
         Ok(row.get("total"))
     }
-}
\ No newline at end of file
+}
+
+/// Point a document at new file contents within a transaction, resetting its OCR
+/// results and dropping everything derived from the old pages
+pub(crate) async fn replace_document_file_in(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    document_id: Uuid,
+    file_path: &str,
+    file_size: i64,
+    file_hash: &str,
+) -> Result<Document> {
+    let query = format!(
+        r#"
+        UPDATE documents
+        SET file_path = $2, file_size = $3, file_hash = $4,
+            content = NULL, ocr_text = NULL, ocr_confidence = NULL, ocr_word_count = NULL,
+            ocr_processing_time_ms = NULL, ocr_status = 'pending', ocr_error = NULL,
+            ocr_completed_at = NULL, ocr_failure_reason = NULL, updated_at = NOW()
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        DOCUMENT_FIELDS
+    );
+    let row = sqlx::query(&query)
+        .bind(document_id)
+        .bind(file_path)
+        .bind(file_size)
+        .bind(file_hash)
+        .fetch_one(&mut **tx)
+        .await?;
+
+    sqlx::query("DELETE FROM page_artifacts WHERE document_id = $1")
+        .bind(document_id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("DELETE FROM vision_fallback_pages WHERE document_id = $1")
+        .bind(document_id)
+        .execute(&mut **tx)
+        .await?;
+    // Personal data found in the old text may be gone (or redacted) from the new one
+    sqlx::query("DELETE FROM document_pii_findings WHERE document_id = $1")
+        .bind(document_id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("DELETE FROM document_pii_scans WHERE document_id = $1")
+        .bind(document_id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("DELETE FROM document_page_transforms WHERE document_id = $1")
+        .bind(document_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(map_row_to_document(&row))
+}
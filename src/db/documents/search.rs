@@ -1,52 +1,67 @@
 use anyhow::Result;
-use sqlx::{QueryBuilder, Postgres, Row};
+use sqlx::{postgres::PgRow, PgPool, QueryBuilder, Postgres, Row};
 use uuid::Uuid;
 
-use crate::models::{Document, UserRole, SearchRequest, SearchMode, SearchSnippet, HighlightRange, EnhancedDocumentResponse};
-use super::helpers::{map_row_to_document, apply_role_based_filter, apply_pagination, find_word_boundary, DOCUMENT_FIELDS};
+use crate::models::{Document, SharePermission, UserRole, SearchRequest, SearchField, SearchMode, SearchSnippet, HighlightRange, EnhancedDocumentResponse, FacetItem, RelatedDocument, SearchResultFacets, WorkflowState};
+use super::helpers::{map_row_to_document, apply_shared_access_filter, apply_pagination, find_word_boundary, DOCUMENT_FIELDS, INDEXED_TEXT};
 use crate::db::Database;
+use crate::ocr::PAGE_BREAK;
+use crate::routes::labels::MAX_LABEL_DEPTH;
+use crate::utils::geo::{location_filters, LocationFilter, KM_PER_DEGREE_LATITUDE};
+use crate::utils::search_query::{parse as parse_search_query, CustomFieldCondition, DateRange, FieldFilter, QueryNode, SearchQuery};
+use crate::utils::text_fold::{fold, FoldedText};
 
 impl Database {
-    /// Performs basic document search with PostgreSQL full-text search
+    /// Performs basic document search with PostgreSQL full-text search over the user's
+    /// own documents and those shared with them
     pub async fn search_documents(&self, user_id: Uuid, search_request: &SearchRequest) -> Result<Vec<Document>> {
-        let mut query = QueryBuilder::<Postgres>::new("SELECT ");
-        query.push(DOCUMENT_FIELDS);
-        query.push(" FROM documents WHERE user_id = ");
-        query.push_bind(user_id);
-
-        // Add search conditions
-        if !search_request.query.trim().is_empty() {
-            query.push(" AND (to_tsvector('english', COALESCE(content, '')) @@ plainto_tsquery('english', ");
-            query.push_bind(&search_request.query);
-            query.push(") OR to_tsvector('english', COALESCE(ocr_text, '')) @@ plainto_tsquery('english', ");
-            query.push_bind(&search_request.query);
-            query.push("))");
-        }
+        let root = parse_search_query(&search_request.query)?.root;
+        let locations = location_filters(search_request.near.as_deref(), search_request.bbox.as_deref())?;
+
+        let rows = self.read_with_fallback(|pool| {
+            let mut query = QueryBuilder::<Postgres>::new("SELECT ");
+            query.push(DOCUMENT_FIELDS);
+            query.push(" FROM documents");
+
+            query.push(" WHERE 1=1");
+            apply_shared_access_filter(&mut query, user_id, UserRole::User, SharePermission::View);
+            push_review_filter(&mut query, user_id);
 
-        // Add tag filtering
-        if let Some(ref tags) = search_request.tags {
-            if !tags.is_empty() {
-                query.push(" AND tags && ");
-                query.push_bind(tags);
+            // Add search conditions
+            if let Some(root) = &root {
+                query.push(" AND ");
+                push_query_node(&mut query, root);
             }
-        }
 
-        // Add MIME type filtering
-        if let Some(ref mime_types) = search_request.mime_types {
-            if !mime_types.is_empty() {
-                query.push(" AND mime_type = ANY(");
-                query.push_bind(mime_types);
-                query.push(")");
+            // Add tag filtering
+            if let Some(ref tags) = search_request.tags {
+                if !tags.is_empty() {
+                    query.push(" AND tags && ");
+                    query.push_bind(tags);
+                }
+            }
+
+            // Add MIME type filtering
+            if let Some(ref mime_types) = search_request.mime_types {
+                if !mime_types.is_empty() {
+                    query.push(" AND mime_type = ANY(");
+                    query.push_bind(mime_types);
+                    query.push(")");
+                }
             }
-        }
 
-        query.push(" ORDER BY created_at DESC");
-        
-        let limit = search_request.limit.unwrap_or(25);
-        let offset = search_request.offset.unwrap_or(0);
-        apply_pagination(&mut query, limit, offset);
+            for location in &locations {
+                push_location_filter(&mut query, location);
+            }
 
-        let rows = query.build().fetch_all(&self.pool).await?;
+            query.push(" ORDER BY created_at DESC");
+
+            let limit = search_request.limit.unwrap_or(25);
+            let offset = search_request.offset.unwrap_or(0);
+            apply_pagination(&mut query, limit, offset);
+
+            async move { query.build().fetch_all(&pool).await }
+        }).await?;
         Ok(rows.iter().map(map_row_to_document).collect())
     }
 
@@ -57,108 +72,78 @@ impl Database {
 
     /// Enhanced search with role-based access control
     pub async fn enhanced_search_documents_with_role(&self, user_id: Uuid, user_role: UserRole, search_request: &SearchRequest) -> Result<Vec<EnhancedDocumentResponse>> {
-        let search_query = search_request.query.trim();
         let include_snippets = search_request.include_snippets.unwrap_or(true);
         let snippet_length = search_request.snippet_length.unwrap_or(200) as usize;
+        let text_search = TextSearch::new(search_request)?;
+        let highlight_text = text_search.highlight_text.clone();
 
-        let mut query = QueryBuilder::<Postgres>::new("SELECT ");
-        query.push(DOCUMENT_FIELDS);
-        
-        // Add search ranking if there's a query
-        if !search_query.is_empty() {
-            match search_request.search_mode.as_ref().unwrap_or(&SearchMode::Simple) {
-                SearchMode::Simple => {
-                    query.push(", ts_rank(to_tsvector('english', COALESCE(content, '') || ' ' || COALESCE(ocr_text, '')), plainto_tsquery('english', ");
-                    query.push_bind(search_query);
-                    query.push(")) as search_rank");
-                }
-                SearchMode::Phrase => {
-                    query.push(", ts_rank(to_tsvector('english', COALESCE(content, '') || ' ' || COALESCE(ocr_text, '')), phraseto_tsquery('english', ");
-                    query.push_bind(search_query);
-                    query.push(")) as search_rank");
+        let rows = self.read_with_fallback(|pool| {
+            let mut query = QueryBuilder::<Postgres>::new("SELECT ");
+            query.push(DOCUMENT_FIELDS);
+
+            // Add search ranking if there's a query
+            match text_search.mode {
+                Some(SearchMode::Fuzzy) => {
+                    query.push(", ");
+                    push_word_similarity(&mut query, &text_search.query);
+                    query.push(" as search_rank, ARRAY['body']::text[] AS matched_fields");
                 }
-                SearchMode::Boolean => {
-                    query.push(", ts_rank(to_tsvector('english', COALESCE(content, '') || ' ' || COALESCE(ocr_text, '')), to_tsquery('english', ");
-                    query.push_bind(search_query);
-                    query.push(")) as search_rank");
+                _ if text_search.rank_query().is_some() => {
+                    query.push(", ts_rank(search_vector, search_query)");
+                    push_field_rank(&mut query);
+                    query.push(" as search_rank, ");
+                    push_matched_fields(&mut query);
                 }
-                SearchMode::Fuzzy => {
-                    query.push(", similarity(COALESCE(content, '') || ' ' || COALESCE(ocr_text, ''), ");
-                    query.push_bind(search_query);
-                    query.push(") as search_rank");
+                _ => {
+                    query.push(", 0.0 as search_rank, ARRAY[]::text[] AS matched_fields");
                 }
             }
-        } else {
-            query.push(", 0.0 as search_rank");
-        }
-
-        query.push(" FROM documents WHERE 1=1");
 
-        apply_role_based_filter(&mut query, user_id, user_role);
+            push_search_scope(&mut query, &text_search, user_id, user_role, search_request);
 
-        // Add search conditions
-        if !search_query.is_empty() {
-            match search_request.search_mode.as_ref().unwrap_or(&SearchMode::Simple) {
-                SearchMode::Simple => {
-                    query.push(" AND (to_tsvector('english', COALESCE(content, '')) @@ plainto_tsquery('english', ");
-                    query.push_bind(search_query);
-                    query.push(") OR to_tsvector('english', COALESCE(ocr_text, '')) @@ plainto_tsquery('english', ");
-                    query.push_bind(search_query);
-                    query.push("))");
-                }
-                SearchMode::Phrase => {
-                    query.push(" AND (to_tsvector('english', COALESCE(content, '')) @@ phraseto_tsquery('english', ");
-                    query.push_bind(search_query);
-                    query.push(") OR to_tsvector('english', COALESCE(ocr_text, '')) @@ phraseto_tsquery('english', ");
-                    query.push_bind(search_query);
-                    query.push("))");
-                }
-                SearchMode::Boolean => {
-                    query.push(" AND (to_tsvector('english', COALESCE(content, '')) @@ to_tsquery('english', ");
-                    query.push_bind(search_query);
-                    query.push(") OR to_tsvector('english', COALESCE(ocr_text, '')) @@ to_tsquery('english', ");
-                    query.push_bind(search_query);
-                    query.push("))");
-                }
-                SearchMode::Fuzzy => {
-                    query.push(" AND similarity(COALESCE(content, '') || ' ' || COALESCE(ocr_text, ''), ");
-                    query.push_bind(search_query);
-                    query.push(") > 0.3");
-                }
-            }
-        }
+            query.push(" ORDER BY search_rank DESC, created_at DESC");
 
-        // Add filtering
-        if let Some(ref tags) = search_request.tags {
-            if !tags.is_empty() {
-                query.push(" AND tags && ");
-                query.push_bind(tags);
-            }
-        }
+            let limit = search_request.limit.unwrap_or(25);
+            let offset = search_request.offset.unwrap_or(0);
+            apply_pagination(&mut query, limit, offset);
 
-        if let Some(ref mime_types) = search_request.mime_types {
-            if !mime_types.is_empty() {
-                query.push(" AND mime_type = ANY(");
-                query.push_bind(mime_types);
-                query.push(")");
-            }
-        }
+            let fuzzy_user = text_search.fuzzy_user(user_id);
+            async move { fetch_search_rows(&pool, query, fuzzy_user).await }
+        }).await?;
 
-        query.push(" ORDER BY search_rank DESC, created_at DESC");
-        
-        let limit = search_request.limit.unwrap_or(25);
-        let offset = search_request.offset.unwrap_or(0);
-        apply_pagination(&mut query, limit, offset);
+        let with_snippets = include_snippets && !highlight_text.is_empty();
+        let row_fields: Vec<Vec<SearchField>> = rows
+            .iter()
+            .map(|row| {
+                let fields: Vec<String> = row.try_get("matched_fields").unwrap_or_default();
+                fields.iter().filter_map(|field| SearchField::parse(field)).collect()
+            })
+            .collect();
 
-        let rows = query.build().fetch_all(&self.pool).await?;
+        // Annotations, comments and custom fields are only loaded for documents they matched in
+        let field_texts = if with_snippets {
+            let ids: Vec<Uuid> = rows
+                .iter()
+                .zip(&row_fields)
+                .filter(|(_, fields)| fields.iter().any(|field| SearchField::INDEXED.contains(field)))
+                .map(|(row, _)| row.get("id"))
+                .collect();
+            self.get_document_field_texts(&ids).await?
+        } else {
+            Vec::new()
+        };
 
         let mut results = Vec::new();
-        for row in rows {
+        for (row, matched_fields) in rows.into_iter().zip(row_fields) {
             let document = map_row_to_document(&row);
             let search_rank: f32 = row.try_get("search_rank").unwrap_or(0.0);
 
-            let snippets = if include_snippets && !search_query.is_empty() {
-                self.generate_snippets(&document, search_query, snippet_length).await
+            let snippets = if with_snippets {
+                let mut snippets = self.generate_snippets(&document, &highlight_text, snippet_length).await;
+                for (_, field, text) in field_texts.iter().filter(|(id, _, _)| *id == document.id) {
+                    snippets.extend(field_snippets(text, field, &highlight_text, snippet_length));
+                }
+                snippets
             } else {
                 Vec::new()
             };
@@ -178,12 +163,203 @@ impl Database {
                 ocr_status: document.ocr_status,
                 search_rank: Some(search_rank),
                 snippets,
+                matched_fields,
             });
         }
 
         Ok(results)
     }
 
+    /// Whether a single document is among the results of a search, applying the same
+    /// text, tag and MIME type conditions and access rules as the enhanced search
+    pub async fn document_matches_search(
+        &self,
+        document_id: Uuid,
+        user_id: Uuid,
+        search_request: &SearchRequest,
+    ) -> Result<bool> {
+        let text_search = TextSearch::new(search_request)?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT EXISTS (SELECT 1");
+        push_search_scope(&mut query, &text_search, user_id, UserRole::User, search_request);
+        query.push(" AND id = ");
+        query.push_bind(document_id);
+        query.push(")");
+
+        let rows = fetch_search_rows(&self.pool, query, text_search.fuzzy_user(user_id)).await?;
+        Ok(rows.first().is_some_and(|row| row.get::<bool, _>(0)))
+    }
+
+    /// Ids of the documents matching a search, oldest first, with the same conditions
+    /// and access rules as the enhanced search but without paging
+    pub async fn search_document_ids(
+        &self,
+        user_id: Uuid,
+        user_role: UserRole,
+        search_request: &SearchRequest,
+        limit: i64,
+    ) -> Result<Vec<Uuid>> {
+        let text_search = TextSearch::new(search_request)?;
+
+        let ids: Vec<Uuid> = self.read_with_fallback(|pool| {
+            let mut query = QueryBuilder::<Postgres>::new("SELECT id");
+            push_search_scope(&mut query, &text_search, user_id, user_role, search_request);
+            query.push(" ORDER BY created_at, id LIMIT ");
+            query.push_bind(limit);
+
+            let fuzzy_user = text_search.fuzzy_user(user_id);
+            async move {
+                let rows = fetch_search_rows(&pool, query, fuzzy_user).await?;
+                Ok(rows.iter().map(|row| row.get(0)).collect())
+            }
+        }).await?;
+        Ok(ids)
+    }
+
+    /// Counts of the documents matching a search by label, MIME type, source,
+    /// correspondent and year, computed over all matches rather than one page
+    pub async fn search_result_facets(
+        &self,
+        user_id: Uuid,
+        user_role: UserRole,
+        search_request: &SearchRequest,
+    ) -> Result<SearchResultFacets> {
+        let text_search = TextSearch::new(search_request)?;
+
+        let rows = self.read_with_fallback(|pool| {
+            let mut query = QueryBuilder::<Postgres>::new(
+                "WITH matches AS (SELECT id, mime_type, tags, source_id, source_type, source_metadata, created_at",
+            );
+            push_search_scope(&mut query, &text_search, user_id, user_role, search_request);
+            query.push(
+                r#")
+                SELECT 'label' AS facet, value, COUNT(DISTINCT id) AS count FROM (
+                    SELECT id, unnest(tags) AS value FROM matches
+                    UNION ALL
+                    SELECT m.id, l.name FROM matches m
+                    JOIN document_labels dl ON dl.document_id = m.id
+                    JOIN labels l ON l.id = dl.label_id
+                ) label_values GROUP BY value
+                UNION ALL
+                SELECT 'mime_type', mime_type, COUNT(*) FROM matches GROUP BY mime_type
+                UNION ALL
+                SELECT 'source', COALESCE(s.name, m.source_type), COUNT(*) FROM matches m
+                LEFT JOIN sources s ON s.id = m.source_id
+                WHERE COALESCE(s.name, m.source_type) IS NOT NULL
+                GROUP BY COALESCE(s.name, m.source_type)
+                UNION ALL
+                SELECT 'correspondent', c.name, COUNT(*) FROM matches m
+                JOIN document_correspondents dc ON dc.document_id = m.id
+                JOIN correspondents c ON c.id = dc.correspondent_id
+                GROUP BY c.name
+                UNION ALL
+                SELECT 'year', EXTRACT(YEAR FROM created_at)::int::text, COUNT(*) FROM matches
+                GROUP BY EXTRACT(YEAR FROM created_at)::int::text"#,
+            );
+
+            let fuzzy_user = text_search.fuzzy_user(user_id);
+            async move { fetch_search_rows(&pool, query, fuzzy_user).await }
+        }).await?;
+
+        let mut facets = SearchResultFacets::default();
+        for row in rows {
+            let facet: String = row.get("facet");
+            let item = FacetItem { value: row.get("value"), count: row.get("count") };
+            match facet.as_str() {
+                "label" => facets.labels.push(item),
+                "mime_type" => facets.mime_types.push(item),
+                "source" => facets.sources.push(item),
+                "correspondent" => facets.correspondents.push(item),
+                _ => facets.years.push(item),
+            }
+        }
+        for items in [&mut facets.labels, &mut facets.mime_types, &mut facets.sources, &mut facets.correspondents] {
+            items.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            items.truncate(MAX_FACET_VALUES);
+        }
+        // Newest year first
+        facets.years.sort_by(|a, b| b.value.cmp(&a.value));
+
+        Ok(facets)
+    }
+
+    /// The most distinctive terms of a document's text: lexemes weighted by how often
+    /// they occur in it and how rare they are among all documents (tf-idf). Terms no
+    /// other document contains are left out, they cannot relate documents.
+    pub async fn get_distinctive_terms(&self, document_id: Uuid, limit: i64) -> Result<Vec<String>> {
+        let terms = sqlx::query_scalar::<_, String>(
+            r#"
+            WITH terms AS (
+                SELECT word, nentry
+                FROM ts_stat(format('SELECT search_vector FROM documents WHERE id = %L', $1::text))
+                WHERE length(word) >= 3 AND word !~ '^[[:digit:][:punct:]]+$'
+                ORDER BY nentry DESC
+                LIMIT 100
+            ),
+            weighted AS (
+                SELECT t.word, t.nentry,
+                       (SELECT COUNT(*) FROM documents d WHERE d.search_vector @@ quote_literal(t.word)::tsquery) AS document_count
+                FROM terms t
+            )
+            SELECT word FROM weighted
+            WHERE document_count > 1
+            ORDER BY nentry * ln((SELECT COUNT(*) FROM documents)::float8 / document_count) DESC, word
+            LIMIT $2
+            "#,
+        )
+        .bind(document_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(terms)
+    }
+
+    /// Documents the user can see that contain the most of the given terms, ranked by
+    /// text similarity, excluding the document the terms came from
+    pub async fn get_related_documents(
+        &self,
+        document_id: Uuid,
+        terms: &[String],
+        user_id: Uuid,
+        user_role: UserRole,
+        limit: i64,
+    ) -> Result<Vec<RelatedDocument>> {
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT id, original_filename, mime_type, file_size, tags, created_at, \
+             ts_rank(search_vector, related_query, 32) AS score, \
+             ARRAY(SELECT term FROM unnest(related_terms) AS term \
+                   WHERE search_vector @@ quote_literal(term)::tsquery) AS shared_terms \
+             FROM documents, (SELECT array_agg(term) AS related_terms, \
+                   string_agg(quote_literal(term), ' | ')::tsquery AS related_query FROM unnest(",
+        );
+        query.push_bind(terms.to_vec());
+        query.push("::text[]) AS term) AS related WHERE search_vector @@ related_query AND id <> ");
+        query.push_bind(document_id);
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::View);
+        query.push(" ORDER BY score DESC, created_at DESC LIMIT ");
+        query.push_bind(limit);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| RelatedDocument {
+                id: row.get("id"),
+                original_filename: row.get("original_filename"),
+                mime_type: row.get("mime_type"),
+                file_size: row.get("file_size"),
+                tags: row.get("tags"),
+                created_at: row.get("created_at"),
+                score: row.get("score"),
+                shared_terms: row.get("shared_terms"),
+            })
+            .collect())
+    }
+
     /// Generates search snippets with highlighted matches
     pub async fn generate_snippets(&self, document: &Document, search_query: &str, snippet_length: usize) -> Vec<SearchSnippet> {
         let mut snippets = Vec::new();
@@ -195,65 +371,800 @@ impl Database {
             ("ocr_text", document.ocr_text.as_deref().unwrap_or(""))
         ];
 
-        for (_source, text) in texts {
+        for (source, text) in texts {
             if text.is_empty() {
                 continue;
             }
+            let paginated = text.contains(PAGE_BREAK) || document.mime_type == "application/pdf";
 
-            let text_lower = text.to_lowercase();
-            for term in &search_terms {
-                let term_lower = term.to_lowercase();
-                let mut start_pos = 0;
-
-                while let Some(match_pos) = text_lower[start_pos..].find(&term_lower) {
-                    let absolute_match_pos = start_pos + match_pos;
-                    
-                    // Calculate snippet boundaries
-                    let snippet_start = if absolute_match_pos >= snippet_length / 2 {
-                        find_word_boundary(text, absolute_match_pos - snippet_length / 2, false)
-                    } else {
-                        0
-                    };
-
-                    let snippet_end = {
-                        let desired_end = snippet_start + snippet_length;
-                        if desired_end < text.len() {
-                            find_word_boundary(text, desired_end, true)
-                        } else {
-                            text.len()
-                        }
-                    };
-
-                    let snippet_text = &text[snippet_start..snippet_end];
-                    
-                    // Calculate highlight range relative to snippet
-                    let highlight_start = absolute_match_pos - snippet_start;
-                    let highlight_end = highlight_start + term.len();
-
-                    let highlight_ranges = vec![HighlightRange {
-                        start: highlight_start as i32,
-                        end: highlight_end as i32,
-                    }];
-
-                    snippets.push(SearchSnippet {
-                        text: snippet_text.to_string(),
-                        start_offset: snippet_start as i32,
-                        end_offset: snippet_end as i32,
-                        highlight_ranges,
-                    });
-
-                    start_pos = absolute_match_pos + term.len();
-                    
-                    // Limit snippets per term
-                    if snippets.len() >= 3 {
-                        break;
-                    }
-                }
-            }
+            push_term_snippets(&mut snippets, text, source, paginated, &search_terms, snippet_length);
         }
 
         // Remove duplicates and limit total snippets
         snippets.truncate(5);
         snippets
     }
-}
\ No newline at end of file
+
+    /// The text of the annotations, comments and custom fields of documents, as
+    /// indexed for search: document id, field and text
+    pub async fn get_document_field_texts(&self, document_ids: &[Uuid]) -> Result<Vec<(Uuid, String, String)>> {
+        if document_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids = document_ids.to_vec();
+        let rows = self.read_with_fallback(|pool| {
+            let ids = ids.clone();
+            async move {
+                sqlx::query_as::<_, (Uuid, String, String)>(
+                    "SELECT document_id, field, text FROM document_field_search WHERE document_id = ANY($1) ORDER BY field",
+                )
+                .bind(ids)
+                .fetch_all(&pool)
+                .await
+            }
+        }).await?;
+        Ok(rows)
+    }
+
+    /// Every occurrence of a phrase in a document's OCR text, or its content when it
+    /// has no OCR text, in order, with the page it is on. Case, accents and the kind
+    /// of whitespace between words are ignored. Returns the total number of
+    /// occurrences and snippets for the `offset..offset + limit` ones.
+    pub fn find_in_document(
+        &self,
+        document: &Document,
+        phrase: &str,
+        snippet_length: usize,
+        offset: usize,
+        limit: usize,
+    ) -> (usize, Vec<SearchSnippet>) {
+        let (source, text) = match document.ocr_text.as_deref().filter(|text| !text.is_empty()) {
+            Some(text) => ("ocr_text", text),
+            None => ("content", document.content.as_deref().unwrap_or("")),
+        };
+        let paginated = text.contains(PAGE_BREAK) || document.mime_type == "application/pdf";
+
+        let mut matches = Vec::new();
+        for expand_umlauts in [false, true] {
+            let words: Vec<String> = phrase.split_whitespace().map(|word| fold(word, expand_umlauts)).collect();
+            let folded = FoldedText::new(text, expand_umlauts);
+            matches = phrase_matches(&folded.text, &words)
+                .into_iter()
+                .map(|(start, end)| folded.original_range(start, end))
+                .collect();
+            if !matches.is_empty() {
+                break;
+            }
+        }
+
+        let snippets = matches
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|&(start, end)| snippet_around(text, source, paginated, start, end, snippet_length))
+            .collect();
+        (matches.len(), snippets)
+    }
+
+    /// Recomputes search vectors, e.g. after a user changes their OCR language or the
+    /// folding rules change. Limited to one user's documents when `user_id` is given.
+    /// Returns the number of documents reindexed.
+    pub async fn rebuild_search_vectors(&self, user_id: Option<Uuid>, batch_size: i64) -> Result<u64> {
+        let mut last_id: Option<Uuid> = None;
+        let mut total = 0u64;
+
+        loop {
+            let ids = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                UPDATE documents
+                SET search_vector = readur_document_search_vector(user_id, content, ocr_text)
+                WHERE id IN (
+                    SELECT id FROM documents
+                    WHERE ($1::uuid IS NULL OR user_id = $1)
+                      AND ($2::uuid IS NULL OR id > $2)
+                    ORDER BY id
+                    LIMIT $3
+                )
+                RETURNING id
+                "#
+            )
+            .bind(user_id)
+            .bind(last_id)
+            .bind(batch_size)
+            .fetch_all(&self.pool)
+            .await?;
+
+            let Some(max_id) = ids.iter().max().copied() else {
+                break;
+            };
+            total += ids.len() as u64;
+            last_id = Some(max_id);
+        }
+
+        // Annotations, comments and custom fields are indexed in the same language
+        sqlx::query(
+            r#"
+            UPDATE document_field_search fs
+            SET search_vector = readur_document_field_search_vector(fs.document_id, fs.text)
+            FROM documents d
+            WHERE d.id = fs.document_id AND ($1::uuid IS NULL OR d.user_id = $1)
+            "#
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(total)
+    }
+}
+
+/// Values listed per facet, most frequent first
+const MAX_FACET_VALUES: usize = 20;
+
+/// Fuzzy matching threshold for users without settings, the default of
+/// `fuzzy_search_threshold`
+const DEFAULT_FUZZY_THRESHOLD: f32 = 0.5;
+
+/// The text part of a search request, resolved once per search
+struct TextSearch {
+    /// The trimmed query text
+    query: String,
+    /// None when there is no query text
+    mode: Option<SearchMode>,
+    /// Simple searches use the query language
+    parsed: Option<SearchQuery>,
+    /// Words to rank and highlight by; field filters and exclusions are left out
+    highlight_text: String,
+    /// Where geotagged documents must have been captured, from `near` and `bbox`
+    locations: Vec<LocationFilter>,
+}
+
+impl TextSearch {
+    fn new(search_request: &SearchRequest) -> Result<Self> {
+        let query = search_request.query.trim().to_string();
+        let mode = if query.is_empty() {
+            None
+        } else {
+            Some(search_request.search_mode.clone().unwrap_or_default())
+        };
+        let parsed = match mode {
+            Some(SearchMode::Simple) => Some(parse_search_query(&query)?),
+            _ => None,
+        };
+        let highlight_text = match &parsed {
+            Some(parsed) => parsed.positive_terms().join(" "),
+            None => query.clone(),
+        };
+        let locations = location_filters(search_request.near.as_deref(), search_request.bbox.as_deref())?;
+        Ok(Self { query, mode, parsed, highlight_text, locations })
+    }
+
+    /// The user whose fuzzy matching threshold applies, for fuzzy searches
+    fn fuzzy_user(&self, user_id: Uuid) -> Option<Uuid> {
+        matches!(self.mode, Some(SearchMode::Fuzzy)).then_some(user_id)
+    }
+
+    /// Text and parse mode of the tsquery results are ranked against
+    fn rank_query(&self) -> Option<(&str, &'static str)> {
+        match &self.mode {
+            Some(SearchMode::Simple) if !self.highlight_text.is_empty() => Some((self.highlight_text.as_str(), "plain")),
+            Some(SearchMode::Simple) => None,
+            Some(mode) => tsquery_parse_mode(mode).map(|parse_mode| (self.query.as_str(), parse_mode)),
+            None => None,
+        }
+    }
+}
+
+/// Adds the FROM and WHERE clauses of a search: the documents the user may see that
+/// match its text, tags, MIME types and location. The ranking tsquery is available as `search_query`.
+fn push_search_scope(
+    query: &mut QueryBuilder<'_, Postgres>,
+    text_search: &TextSearch,
+    user_id: Uuid,
+    user_role: UserRole,
+    search_request: &SearchRequest,
+) {
+    query.push(" FROM documents");
+
+    // The folded tsquery is built once per search rather than once per row
+    if let Some((rank_text, parse_mode)) = text_search.rank_query() {
+        query.push(", readur_search_query(");
+        query.push_bind(rank_text.to_string());
+        query.push(", ");
+        query.push_bind(parse_mode);
+        query.push(") AS search_query");
+    }
+
+    query.push(" WHERE 1=1");
+
+    apply_shared_access_filter(query, user_id, user_role, SharePermission::View);
+    push_review_filter(query, user_id);
+
+    // Add search conditions
+    match &text_search.mode {
+        Some(SearchMode::Fuzzy) => {
+            // word_similarity() at or above the threshold `fetch_search_rows` sets, as an
+            // operator the trigram index answers
+            query.push(" AND ");
+            query.push_bind(text_search.query.clone());
+            query.push(format!(" <% {}", INDEXED_TEXT));
+        }
+        Some(SearchMode::Simple) => {
+            if let Some(root) = text_search.parsed.as_ref().and_then(|parsed| parsed.root.as_ref()) {
+                query.push(" AND ");
+                push_query_node(query, root);
+            }
+        }
+        Some(_) => {
+            query.push(
+                " AND (search_vector @@ search_query \
+                 OR id IN (SELECT tr.document_id FROM document_translations tr WHERE tr.search_vector @@ search_query) \
+                 OR id IN (SELECT fs.document_id FROM document_field_search fs WHERE fs.search_vector @@ search_query))",
+            );
+        }
+        None => {}
+    }
+
+    // Add filtering
+    if let Some(ref tags) = search_request.tags {
+        if !tags.is_empty() {
+            query.push(" AND tags && ");
+            query.push_bind(tags.clone());
+        }
+    }
+
+    if let Some(ref mime_types) = search_request.mime_types {
+        if !mime_types.is_empty() {
+            query.push(" AND mime_type = ANY(");
+            query.push_bind(mime_types.clone());
+            query.push(")");
+        }
+    }
+
+    for location in &text_search.locations {
+        push_location_filter(query, location);
+    }
+}
+
+/// Leaves out documents that have not been reviewed yet, for users who require review
+/// before documents show up in search
+fn push_review_filter(query: &mut QueryBuilder<'_, Postgres>, user_id: Uuid) {
+    let reviewed: Vec<&str> = WorkflowState::ALL.into_iter().filter(|s| s.is_reviewed()).map(WorkflowState::as_str).collect();
+    query.push(" AND (workflow_state::text = ANY(");
+    query.push_bind(reviewed);
+    query.push(") OR NOT COALESCE((SELECT require_review_before_search FROM settings WHERE user_id = ");
+    query.push_bind(user_id);
+    query.push("), false))");
+}
+
+/// Keeps the documents whose coordinates are within the filter. A radius search first
+/// narrows to the band of latitudes it can reach, so the index does most of the work.
+fn push_location_filter(query: &mut QueryBuilder<'_, Postgres>, location: &LocationFilter) {
+    query.push(" AND id IN (SELECT dc.document_id FROM document_coordinates dc WHERE ");
+    match *location {
+        LocationFilter::Near { latitude, longitude, radius_km } => {
+            let band = radius_km / KM_PER_DEGREE_LATITUDE;
+            query.push("dc.latitude BETWEEN ");
+            query.push_bind(latitude - band);
+            query.push(" AND ");
+            query.push_bind(latitude + band);
+            query.push(" AND readur_distance_km(dc.latitude, dc.longitude, ");
+            query.push_bind(latitude);
+            query.push(", ");
+            query.push_bind(longitude);
+            query.push(") <= ");
+            query.push_bind(radius_km);
+        }
+        LocationFilter::BoundingBox { south, west, north, east } => {
+            query.push("dc.latitude BETWEEN ");
+            query.push_bind(south);
+            query.push(" AND ");
+            query.push_bind(north);
+            // A box crossing the antimeridian runs from west to 180 and from -180 to east
+            let joiner = if west <= east { " AND " } else { " OR " };
+            query.push(" AND (dc.longitude >= ");
+            query.push_bind(west);
+            query.push(joiner);
+            query.push("dc.longitude <= ");
+            query.push_bind(east);
+            query.push(")");
+        }
+    }
+    query.push(")");
+}
+
+/// Trigram similarity of the query to the closest stretch of a document's text, so a
+/// misspelt "invioce" still finds "invoice" (`word_similarity` of pg_trgm)
+fn push_word_similarity(query: &mut QueryBuilder<'_, Postgres>, text: &str) {
+    query.push("word_similarity(");
+    query.push_bind(text.to_string());
+    query.push(format!(", {})", INDEXED_TEXT));
+}
+
+/// Run a search query. Fuzzy searches, given the user, run in a transaction that first
+/// sets the `<%` threshold to the user's `fuzzy_search_threshold`.
+async fn fetch_search_rows(
+    pool: &PgPool,
+    mut query: QueryBuilder<'_, Postgres>,
+    fuzzy_user: Option<Uuid>,
+) -> Result<Vec<PgRow>, sqlx::Error> {
+    let Some(user_id) = fuzzy_user else {
+        return query.build().fetch_all(pool).await;
+    };
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "SELECT set_config('pg_trgm.word_similarity_threshold', \
+         COALESCE((SELECT fuzzy_search_threshold FROM settings WHERE user_id = $1), $2)::text, true)",
+    )
+    .bind(user_id)
+    .bind(DEFAULT_FUZZY_THRESHOLD)
+    .execute(&mut *tx)
+    .await?;
+    let rows = query.build().fetch_all(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(rows)
+}
+
+/// Adds the condition of a parsed search query, see `utils::search_query`
+fn push_query_node(query: &mut QueryBuilder<'_, Postgres>, node: &QueryNode) {
+    match node {
+        QueryNode::Text(text) => push_text_match(query, text, "plain"),
+        QueryNode::Phrase(phrase) => push_text_match(query, phrase, "phrase"),
+        QueryNode::Field(filter) => push_field_filter(query, filter),
+        QueryNode::And(nodes) | QueryNode::Or(nodes) => {
+            let operator = if matches!(node, QueryNode::And(_)) { " AND " } else { " OR " };
+            query.push("(");
+            for (i, node) in nodes.iter().enumerate() {
+                if i > 0 {
+                    query.push(operator);
+                }
+                push_query_node(query, node);
+            }
+            query.push(")");
+        }
+        QueryNode::Not(node) => {
+            // Unknown counts as not matching, so `-source:webdav` keeps uploads without a source
+            query.push("NOT COALESCE((");
+            push_query_node(query, node);
+            query.push("), FALSE)");
+        }
+    }
+}
+
+/// Documents whose text, the text of one of their translations, or their annotations,
+/// comments or custom fields match
+fn push_text_match(query: &mut QueryBuilder<'_, Postgres>, text: &str, parse_mode: &'static str) {
+    query.push("(search_vector @@ readur_search_query(");
+    query.push_bind(text.to_string());
+    query.push(", ");
+    query.push_bind(parse_mode);
+    query.push(") OR id IN (SELECT tr.document_id FROM document_translations tr WHERE tr.search_vector @@ readur_search_query(");
+    query.push_bind(text.to_string());
+    query.push(", ");
+    query.push_bind(parse_mode);
+    query.push(")) OR id IN (SELECT fs.document_id FROM document_field_search fs WHERE fs.search_vector @@ readur_search_query(");
+    query.push_bind(text.to_string());
+    query.push(", ");
+    query.push_bind(parse_mode);
+    query.push(")))");
+}
+
+/// Adds the ranks of the document's annotations, comments and custom fields that match
+/// `search_query` to the rank before it, each times the boost of its field
+fn push_field_rank(query: &mut QueryBuilder<'_, Postgres>) {
+    query.push(" + COALESCE((SELECT SUM(ts_rank(fs.search_vector, search_query) * CASE fs.field");
+    for field in SearchField::INDEXED {
+        query.push(" WHEN ");
+        query.push_bind(field.as_str());
+        query.push(" THEN ");
+        query.push_bind(field.boost());
+    }
+    query.push(
+        " ELSE 1 END) FROM document_field_search fs \
+         WHERE fs.document_id = documents.id AND fs.search_vector @@ search_query), 0)",
+    );
+}
+
+/// Adds the fields that match `search_query` as `matched_fields`, see `SearchField`
+fn push_matched_fields(query: &mut QueryBuilder<'_, Postgres>) {
+    query.push(
+        "ARRAY_REMOVE(ARRAY[\
+             CASE WHEN search_vector @@ search_query THEN 'body' END, \
+             CASE WHEN EXISTS (SELECT 1 FROM document_translations tr \
+                 WHERE tr.document_id = documents.id AND tr.search_vector @@ search_query) THEN 'translation' END\
+         ], NULL) || ARRAY(SELECT fs.field FROM document_field_search fs \
+             WHERE fs.document_id = documents.id AND fs.search_vector @@ search_query ORDER BY fs.field) AS matched_fields",
+    );
+}
+
+/// Ids of the labels named `name` and of all labels nested under them, so a parent label
+/// also finds documents labeled with its children
+fn push_label_with_descendants(query: &mut QueryBuilder<'_, Postgres>, name: &str) {
+    query.push(
+        "WITH RECURSIVE label_tree AS (SELECT id, 1 AS depth FROM labels WHERE lower(name) = lower(",
+    );
+    query.push_bind(name.to_string());
+    query.push(
+        ") UNION ALL SELECT l.id, t.depth + 1 FROM labels l JOIN label_tree t ON l.parent_id = t.id WHERE t.depth < ",
+    );
+    query.push_bind(MAX_LABEL_DEPTH);
+    query.push(") SELECT id FROM label_tree");
+}
+
+fn push_field_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &FieldFilter) {
+    match filter {
+        FieldFilter::Label(name) => {
+            query.push("(EXISTS (SELECT 1 FROM unnest(tags) AS tag WHERE lower(tag) = lower(");
+            query.push_bind(name.clone());
+            query.push(")) OR id IN (SELECT dl.document_id FROM document_labels dl WHERE dl.label_id IN (");
+            push_label_with_descendants(query, name);
+            query.push(")))");
+        }
+        FieldFilter::Source(source) => {
+            query.push("(lower(source_type) = lower(");
+            query.push_bind(source.clone());
+            query.push(") OR source_id IN (SELECT s.id FROM sources s WHERE lower(s.name) = lower(");
+            query.push_bind(source.clone());
+            query.push(") OR lower(s.source_type) = lower(");
+            query.push_bind(source.clone());
+            query.push(")))");
+        }
+        FieldFilter::Correspondent(name) => {
+            query.push("id IN (SELECT dc.document_id FROM document_correspondents dc JOIN correspondents c ON c.id = dc.correspondent_id WHERE lower(c.name) = lower(");
+            query.push_bind(name.clone());
+            query.push("))");
+        }
+        FieldFilter::DocumentType(name) => {
+            query.push("id IN (SELECT dcf.document_id FROM document_custom_fields dcf JOIN document_types dt ON dt.id = dcf.document_type_id WHERE lower(dt.name) = lower(");
+            query.push_bind(name.clone());
+            query.push("))");
+        }
+        FieldFilter::CustomField { name, condition } => {
+            query.push("id IN (SELECT dcf.document_id FROM document_custom_fields dcf WHERE ");
+            push_json_condition(query, "dcf.fields", name, condition, false);
+            query.push(")");
+        }
+        FieldFilter::Metadata { key, condition } => {
+            query.push("(");
+            push_json_condition(query, "source_metadata", key, condition, true);
+            query.push(")");
+        }
+        FieldFilter::MimeType(mime_type) => match mime_type.strip_suffix("/*") {
+            Some(major) => {
+                query.push("split_part(lower(mime_type), '/', 1) = ");
+                query.push_bind(major.to_string());
+            }
+            None if mime_type.contains('/') => {
+                query.push("lower(mime_type) = ");
+                query.push_bind(mime_type.clone());
+            }
+            None => {
+                query.push("(split_part(lower(mime_type), '/', 1) = ");
+                query.push_bind(mime_type.clone());
+                query.push(" OR split_part(lower(mime_type), '/', 2) = ");
+                query.push_bind(mime_type.clone());
+                query.push(")");
+            }
+        },
+        FieldFilter::Filename(part) => {
+            query.push("original_filename ILIKE ");
+            query.push_bind(format!("%{}%", escape_like(part)));
+        }
+        FieldFilter::Created(range) => push_date_range(query, "created_at", range),
+        FieldFilter::Modified(range) => push_date_range(query, "updated_at", range),
+    }
+}
+
+/// Condition on a value of a JSON column, `document_custom_fields.fields` or
+/// `documents.source_metadata`. Text matches the whole value, or any part of it with
+/// `partial_text`. Values of another type never match rather than failing the cast.
+/// Exact matches are containment tests and every other condition first requires the
+/// key, so the GIN index on the column narrows the rows before values are compared.
+fn push_json_condition(
+    query: &mut QueryBuilder<'_, Postgres>,
+    json: &str,
+    name: &str,
+    condition: &CustomFieldCondition,
+    partial_text: bool,
+) {
+    if let CustomFieldCondition::Exact(text) = condition {
+        query.push(format!("({} @> ", json));
+        query.push_bind(serde_json::json!({ name: text }));
+        // A number matches whether it was stored as a number or as text
+        if let Some(number) = text.parse::<f64>().ok().filter(|number| number.is_finite()) {
+            query.push(format!(" OR {} @> ", json));
+            query.push_bind(serde_json::json!({ name: number }));
+        }
+        query.push(")");
+        return;
+    }
+
+    query.push(format!("({} ? ", json));
+    query.push_bind(name.to_string());
+    query.push(" AND ");
+    match condition {
+        CustomFieldCondition::Equals(text) if partial_text => {
+            query.push(format!("{}->>", json));
+            query.push_bind(name.to_string());
+            query.push(" ILIKE ");
+            query.push_bind(format!("%{}%", escape_like(text)));
+        }
+        CustomFieldCondition::Equals(text) => {
+            query.push(format!("lower({}->>", json));
+            query.push_bind(name.to_string());
+            query.push(") = lower(");
+            query.push_bind(text.clone());
+            query.push(")");
+        }
+        CustomFieldCondition::Exact(_) => unreachable!("exact matches are containment tests"),
+        CustomFieldCondition::Number { operator, value } => {
+            push_json_number(query, json, name);
+            query.push(format!(" {} ", operator));
+            query.push_bind(*value);
+        }
+        CustomFieldCondition::NumberRange { min, max } => {
+            query.push("(");
+            push_json_number(query, json, name);
+            query.push(" IS NOT NULL");
+            if let Some(min) = min {
+                query.push(" AND ");
+                push_json_number(query, json, name);
+                query.push(" >= ");
+                query.push_bind(*min);
+            }
+            if let Some(max) = max {
+                query.push(" AND ");
+                push_json_number(query, json, name);
+                query.push(" <= ");
+                query.push_bind(*max);
+            }
+            query.push(")");
+        }
+        CustomFieldCondition::Date(range) => {
+            query.push("(");
+            push_json_date(query, json, name);
+            query.push(" IS NOT NULL");
+            if let Some(from) = range.from {
+                query.push(" AND ");
+                push_json_date(query, json, name);
+                query.push(" >= ");
+                query.push_bind(from.date_naive());
+            }
+            if let Some(to) = range.to {
+                query.push(" AND ");
+                push_json_date(query, json, name);
+                query.push(" < ");
+                query.push_bind(to.date_naive());
+            }
+            query.push(")");
+        }
+    }
+    query.push(")");
+}
+
+/// A JSON value as a number, NULL when it is not a JSON number
+fn push_json_number(query: &mut QueryBuilder<'_, Postgres>, json: &str, name: &str) {
+    query.push(format!("CASE WHEN jsonb_typeof({}->", json));
+    query.push_bind(name.to_string());
+    query.push(format!(") = 'number' THEN ({}->>", json));
+    query.push_bind(name.to_string());
+    query.push(")::float8 END");
+}
+
+/// A JSON value as a date, NULL when it is not a valid YYYY-MM-DD date, optionally
+/// followed by a time
+fn push_json_date(query: &mut QueryBuilder<'_, Postgres>, json: &str, name: &str) {
+    query.push(format!("CASE WHEN {}->>", json));
+    query.push_bind(name.to_string());
+    query.push(format!(" ~ '^[0-9]{{4}}-[0-9]{{2}}-[0-9]{{2}}($|T)' THEN readur_try_date(left({}->>", json));
+    query.push_bind(name.to_string());
+    query.push(", 10)) END");
+}
+
+fn push_date_range(query: &mut QueryBuilder<'_, Postgres>, column: &str, range: &DateRange) {
+    query.push("(TRUE");
+    if let Some(from) = range.from {
+        query.push(format!(" AND {} >= ", column));
+        query.push_bind(from);
+    }
+    if let Some(to) = range.to {
+        query.push(format!(" AND {} < ", column));
+        query.push_bind(to);
+    }
+    query.push(")");
+}
+
+/// Escapes the LIKE wildcards in a literal
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Argument to readur_search_query() for modes that use the full-text index
+fn tsquery_parse_mode(mode: &SearchMode) -> Option<&'static str> {
+    match mode {
+        SearchMode::Simple => Some("plain"),
+        SearchMode::Phrase => Some("phrase"),
+        SearchMode::Boolean => Some("boolean"),
+        SearchMode::Fuzzy => None,
+    }
+}
+
+/// 1-based page of the text at a byte offset
+fn page_number_at(text: &str, offset: usize) -> i32 {
+    text[..offset].matches(PAGE_BREAK).count() as i32 + 1
+}
+
+/// Snippet of about `snippet_length` characters around the match at `match_start..match_end`
+fn snippet_around(
+    text: &str,
+    source: &str,
+    paginated: bool,
+    match_start: usize,
+    match_end: usize,
+    snippet_length: usize,
+) -> SearchSnippet {
+    // Calculate snippet boundaries; find_word_boundary works in characters
+    let match_char = text[..match_start].chars().count();
+    let snippet_start_char = match_char.saturating_sub(snippet_length / 2);
+    let snippet_start = if snippet_start_char > 0 {
+        find_word_boundary(text, snippet_start_char, false)
+    } else {
+        0
+    };
+
+    let snippet_end = {
+        let desired_end = snippet_start_char + snippet_length;
+        if desired_end < text.chars().count() {
+            find_word_boundary(text, desired_end, true).max(match_end)
+        } else {
+            text.len()
+        }
+    };
+
+    let snippet_text = &text[snippet_start..snippet_end];
+    let start_char = text[..snippet_start].chars().count();
+    let highlight_start_char = match_char - start_char;
+    let highlight_end_char = highlight_start_char + text[match_start..match_end].chars().count();
+
+    // Calculate highlight range relative to snippet
+    let highlight_ranges = vec![HighlightRange {
+        start: (match_start - snippet_start) as i32,
+        end: (match_end - snippet_start) as i32,
+        start_char: highlight_start_char as i32,
+        end_char: highlight_end_char as i32,
+    }];
+
+    SearchSnippet {
+        // A form feed is one byte and one character, so offsets still line up
+        text: snippet_text.replace(PAGE_BREAK, " "),
+        start_offset: snippet_start as i32,
+        end_offset: snippet_end as i32,
+        start_char: start_char as i32,
+        end_char: (start_char + snippet_text.chars().count()) as i32,
+        source: source.to_string(),
+        page_number: paginated.then(|| page_number_at(text, match_start)),
+        highlight_ranges,
+    }
+}
+
+/// Adds snippets around the occurrences of each term in `text`, stopping at 3.
+/// Accent-insensitive like the search itself; the umlaut-expanded pass lets "Mueller"
+/// and "Müller" find each other.
+fn push_term_snippets(
+    snippets: &mut Vec<SearchSnippet>,
+    text: &str,
+    source: &str,
+    paginated: bool,
+    search_terms: &[&str],
+    snippet_length: usize,
+) {
+    let folded = FoldedText::new(text, false);
+    let mut expanded: Option<FoldedText> = None;
+
+    for term in search_terms {
+        let mut matches = folded_matches(&folded, &fold(term, false));
+        if matches.is_empty() {
+            let expanded = expanded.get_or_insert_with(|| FoldedText::new(text, true));
+            matches = folded_matches(expanded, &fold(term, true));
+        }
+
+        for (match_start, match_end) in matches {
+            snippets.push(snippet_around(text, source, paginated, match_start, match_end, snippet_length));
+
+            // Limit snippets per term
+            if snippets.len() >= 3 {
+                break;
+            }
+        }
+    }
+}
+
+/// Snippets of the text collected from a document's annotations, comments or custom
+/// fields, at most 2 per field
+fn field_snippets(text: &str, field: &str, search_query: &str, snippet_length: usize) -> Vec<SearchSnippet> {
+    let search_terms: Vec<&str> = search_query.split_whitespace().collect();
+    let mut snippets = Vec::new();
+    push_term_snippets(&mut snippets, text, field, false, &search_terms, snippet_length);
+    snippets.truncate(2);
+    snippets
+}
+
+/// Byte ranges of every occurrence of already folded words separated by any whitespace
+fn phrase_matches(text: &str, words: &[String]) -> Vec<(usize, usize)> {
+    let Some((first, rest)) = words.split_first() else {
+        return Vec::new();
+    };
+    if first.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+    'occurrences: for (start, _) in text.match_indices(first.as_str()) {
+        let mut end = start + first.len();
+        for word in rest {
+            let remaining = &text[end..];
+            let next = remaining.trim_start();
+            let gap = remaining.len() - next.len();
+            if gap == 0 || !next.starts_with(word.as_str()) {
+                continue 'occurrences;
+            }
+            end += gap + word.len();
+        }
+        matches.push((start, end));
+    }
+    matches
+}
+
+/// Original byte ranges of every occurrence of an already folded term
+fn folded_matches(folded: &FoldedText, term: &str) -> Vec<(usize, usize)> {
+    if term.is_empty() {
+        return Vec::new();
+    }
+    folded.text
+        .match_indices(term)
+        .map(|(start, _)| folded.original_range(start, start + term.len()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_number_at() {
+        let text = "first page\u{c}second page\u{c}third";
+        assert_eq!(page_number_at(text, 0), 1);
+        assert_eq!(page_number_at(text, text.find("second").unwrap()), 2);
+        assert_eq!(page_number_at(text, text.find("third").unwrap()), 3);
+    }
+
+    #[test]
+    fn test_phrase_matches_across_whitespace() {
+        let words = ["total".to_string(), "due".to_string()];
+        let text = "total due\ntotal\n  due\u{c}totaldue total";
+        assert_eq!(phrase_matches(text, &words), vec![(0, 9), (10, 21)]);
+        assert!(phrase_matches(text, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_field_snippets() {
+        let text = "Check the Müller invoice\nMueller called about the invoice\nPaid by Mueller";
+        let snippets = field_snippets(text, "comments", "muller", 40);
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].source, "comments");
+        assert_eq!(snippets[0].page_number, None);
+        assert_eq!(field_snippets(text, "comments", "invoice", 40).len(), 2);
+        assert!(field_snippets(text, "comments", "receipt", 40).is_empty());
+    }
+
+    #[test]
+    fn test_search_field_names() {
+        for field in SearchField::INDEXED {
+            assert_eq!(SearchField::parse(field.as_str()), Some(field));
+            assert!(field.boost() >= 1.0);
+        }
+        assert_eq!(SearchField::parse("body"), Some(SearchField::Body));
+        assert_eq!(SearchField::parse("notes"), None);
+    }
+}
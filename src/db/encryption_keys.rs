@@ -0,0 +1,250 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::{workspaces::WorkspaceScoped, Database};
+use crate::models::{EncryptionKey, EncryptionKeyEvent, KeyOwner};
+
+const ENCRYPTION_KEY_COLUMNS: &str =
+    "id, user_id, workspace_id, version, status, provider, kms_key_ref, wrapped_key, created_at, retired_at";
+
+/// The column naming the owner of keys and key events, with the owner's id
+fn owner_column(owner: KeyOwner) -> (&'static str, Uuid) {
+    match owner {
+        KeyOwner::User(user_id) => ("user_id", user_id),
+        KeyOwner::Workspace(workspace_id) => ("workspace_id", workspace_id),
+    }
+}
+
+impl Database {
+    pub async fn get_active_encryption_key(&self, owner: KeyOwner) -> Result<Option<EncryptionKey>> {
+        let (column, owner_id) = owner_column(owner);
+        let query = format!(
+            "SELECT {} FROM encryption_keys WHERE {} = $1 AND status = 'active'",
+            ENCRYPTION_KEY_COLUMNS, column
+        );
+        let key = sqlx::query_as::<_, EncryptionKey>(&query)
+            .bind(owner_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(key)
+    }
+
+    pub async fn get_encryption_key(&self, key_id: Uuid) -> Result<Option<EncryptionKey>> {
+        let query = format!("SELECT {} FROM encryption_keys WHERE id = $1", ENCRYPTION_KEY_COLUMNS);
+        let key = sqlx::query_as::<_, EncryptionKey>(&query)
+            .bind(key_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(key)
+    }
+
+    /// All versions of a user's or a workspace's key, newest first
+    pub async fn get_encryption_keys(&self, owner: KeyOwner) -> Result<Vec<EncryptionKey>> {
+        let (column, owner_id) = owner_column(owner);
+        let query = format!(
+            "SELECT {} FROM encryption_keys WHERE {} = $1 ORDER BY version DESC",
+            ENCRYPTION_KEY_COLUMNS, column
+        );
+        let keys = sqlx::query_as::<_, EncryptionKey>(&query)
+            .bind(owner_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(keys)
+    }
+
+    /// Store the first key of a user or workspace. Returns None when a concurrent
+    /// request created one first.
+    pub async fn create_initial_encryption_key(
+        &self,
+        owner: KeyOwner,
+        provider: &str,
+        kms_key_ref: Option<&str>,
+        wrapped_key: &[u8],
+    ) -> Result<Option<EncryptionKey>> {
+        let (user_id, workspace_id) = owner.ids();
+        let query = format!(
+            r#"INSERT INTO encryption_keys (user_id, workspace_id, version, provider, kms_key_ref, wrapped_key)
+               VALUES ($1, $2, 1, $3, $4, $5)
+               ON CONFLICT DO NOTHING
+               RETURNING {}"#,
+            ENCRYPTION_KEY_COLUMNS
+        );
+        let key = sqlx::query_as::<_, EncryptionKey>(&query)
+            .bind(user_id)
+            .bind(workspace_id)
+            .bind(provider)
+            .bind(kms_key_ref)
+            .bind(wrapped_key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(key)
+    }
+
+    /// Retire the active key and store the next version as the new active key
+    pub async fn rotate_encryption_key(
+        &self,
+        owner: KeyOwner,
+        provider: &str,
+        kms_key_ref: Option<&str>,
+        wrapped_key: &[u8],
+    ) -> Result<EncryptionKey> {
+        let (column, owner_id) = owner_column(owner);
+        let (user_id, workspace_id) = owner.ids();
+        let mut tx = self.pool.begin().await?;
+
+        let query = format!(
+            "UPDATE encryption_keys SET status = 'retired', retired_at = NOW() WHERE {} = $1 AND status = 'active'",
+            column
+        );
+        sqlx::query(&query)
+            .bind(owner_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let query = format!(
+            r#"INSERT INTO encryption_keys (user_id, workspace_id, version, provider, kms_key_ref, wrapped_key)
+               SELECT $1, $2, COALESCE(MAX(version), 0) + 1, $3, $4, $5
+               FROM encryption_keys WHERE {} = $6
+               RETURNING {}"#,
+            column, ENCRYPTION_KEY_COLUMNS
+        );
+        let key = sqlx::query_as::<_, EncryptionKey>(&query)
+            .bind(user_id)
+            .bind(workspace_id)
+            .bind(provider)
+            .bind(kms_key_ref)
+            .bind(wrapped_key)
+            .bind(owner_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(key)
+    }
+
+    /// Replace how a key version is wrapped; the key material stays the same
+    pub async fn update_encryption_key_wrapping(
+        &self,
+        key_id: Uuid,
+        provider: &str,
+        kms_key_ref: Option<&str>,
+        wrapped_key: &[u8],
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE encryption_keys SET provider = $2, kms_key_ref = $3, wrapped_key = $4 WHERE id = $1"
+        )
+        .bind(key_id)
+        .bind(provider)
+        .bind(kms_key_ref)
+        .bind(wrapped_key)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_encryption_key_event(
+        &self,
+        key_id: Option<Uuid>,
+        owner: KeyOwner,
+        action: &str,
+        actor_user_id: Option<Uuid>,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        let (user_id, workspace_id) = owner.ids();
+        sqlx::query(
+            r#"INSERT INTO encryption_key_events (key_id, user_id, workspace_id, action, actor_user_id, detail)
+               VALUES ($1, $2, $3, $4, $5, $6)"#
+        )
+        .bind(key_id)
+        .bind(user_id)
+        .bind(workspace_id)
+        .bind(action)
+        .bind(actor_user_id)
+        .bind(detail)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_encryption_key_events(&self, owner: KeyOwner, limit: i64) -> Result<Vec<EncryptionKeyEvent>> {
+        let (column, owner_id) = owner_column(owner);
+        let query = format!(
+            r#"SELECT id, key_id, user_id, workspace_id, action, actor_user_id, detail, created_at
+               FROM encryption_key_events
+               WHERE {} = $1
+               ORDER BY created_at DESC
+               LIMIT $2"#,
+            column
+        );
+        let events = sqlx::query_as::<_, EncryptionKeyEvent>(&query)
+            .bind(owner_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(events)
+    }
+
+    /// `(id, user_id, filename, file_path)` of every document whose file is sealed with
+    /// the owner's key: the documents of a workspace, or the personal documents of a user
+    pub async fn get_key_owner_document_files(&self, owner: KeyOwner) -> Result<Vec<(Uuid, Uuid, String, String)>> {
+        let filter = match owner {
+            KeyOwner::User(_) => "user_id = $1 AND workspace_id IS NULL",
+            KeyOwner::Workspace(_) => "workspace_id = $1",
+        };
+        let query = format!(
+            "SELECT id, user_id, filename, file_path FROM documents WHERE {} ORDER BY created_at",
+            filter
+        );
+        let files = sqlx::query_as::<_, (Uuid, Uuid, String, String)>(&query)
+            .bind(owner_column(owner).1)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(files)
+    }
+
+    /// Ids of the sources whose credentials are sealed with the owner's key
+    pub async fn get_key_owner_source_ids(&self, owner: KeyOwner) -> Result<Vec<Uuid>> {
+        let filter = match owner {
+            KeyOwner::User(_) => "user_id = $1 AND workspace_id IS NULL",
+            KeyOwner::Workspace(_) => "workspace_id = $1",
+        };
+        let query = format!("SELECT id FROM sources WHERE {} ORDER BY created_at", filter);
+        let ids = sqlx::query_scalar::<_, Uuid>(&query)
+            .bind(owner_column(owner).1)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(ids)
+    }
+
+    /// The owner of the key a document's or source's data is sealed with, None when the
+    /// row does not exist (yet)
+    pub async fn get_key_owner(&self, scoped: WorkspaceScoped, id: Uuid) -> Result<Option<KeyOwner>> {
+        let query = format!("SELECT user_id, workspace_id FROM {} WHERE id = $1", scoped.table());
+        let row = sqlx::query_as::<_, (Uuid, Option<Uuid>)>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|(user_id, workspace_id)| KeyOwner::of(user_id, workspace_id)))
+    }
+
+    /// Point a document at its re-encrypted file without touching its OCR state
+    pub async fn set_document_file_path(&self, document_id: Uuid, file_path: &str) -> Result<()> {
+        sqlx::query("UPDATE documents SET file_path = $2, updated_at = NOW() WHERE id = $1")
+            .bind(document_id)
+            .bind(file_path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{EntityMention, GraphEntity, GraphEntitySummary};
+
+/// Nodes of the latest graph version of their document, aliased `n`
+pub(super) const LATEST_NODES: &str = r#"
+    document_nodes n
+    JOIN document_graph_versions v ON v.id = n.version_id
+    WHERE v.version_number = (SELECT MAX(version_number) FROM document_graph_versions WHERE document_id = v.document_id)
+"#;
+
+impl Database {
+    /// The user's entities matching the name fragment and type, by name
+    pub async fn list_graph_entities(
+        &self,
+        user_id: Uuid,
+        name: Option<&str>,
+        label: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<GraphEntitySummary>> {
+        let query = format!(
+            r#"SELECT e.id, e.user_id, e.label, e.name, e.normalized_name, e.created_at,
+                      (SELECT COUNT(DISTINCT n.document_id) FROM {}
+                         AND n.label = e.label AND lower(btrim(n.name)) = e.normalized_name) AS document_count
+               FROM graph_entities e
+               WHERE e.user_id = $1
+                 AND ($2::text IS NULL OR e.normalized_name LIKE '%' || lower(btrim($2)) || '%')
+                 AND ($3::text IS NULL OR e.label = $3)
+               ORDER BY e.normalized_name, e.label
+               LIMIT $4"#,
+            LATEST_NODES
+        );
+        let entities = sqlx::query_as::<_, GraphEntitySummary>(&query)
+            .bind(user_id)
+            .bind(name)
+            .bind(label)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(entities)
+    }
+
+    pub async fn get_graph_entity(&self, id: Uuid) -> Result<Option<GraphEntity>> {
+        let entity = sqlx::query_as::<_, GraphEntity>(
+            "SELECT id, user_id, label, name, normalized_name, created_at FROM graph_entities WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(entity)
+    }
+
+    pub async fn get_graph_entities(&self, ids: &[Uuid]) -> Result<Vec<GraphEntity>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let entities = sqlx::query_as::<_, GraphEntity>(
+            "SELECT id, user_id, label, name, normalized_name, created_at FROM graph_entities WHERE id = ANY($1) ORDER BY normalized_name, label"
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entities)
+    }
+
+    /// Documents of the entity's owner that may mention it, most recent first: those
+    /// whose latest graph names it, and those where a correction renamed something to it
+    pub async fn find_entity_documents(&self, entity: &GraphEntity, limit: i64) -> Result<Vec<EntityMention>> {
+        let query = format!(
+            r#"SELECT d.id AS document_id, d.original_filename AS filename,
+                      COALESCE(d.original_created_at, d.created_at) AS document_date
+               FROM documents d
+               WHERE d.user_id = $1
+                 AND (d.id IN (SELECT n.document_id FROM {} AND n.label = $2 AND lower(btrim(n.name)) = $3)
+                      OR d.id IN (SELECT c.document_id FROM document_graph_corrections c
+                                  WHERE c.kind = 'rename_node' AND c.new_label = $2
+                                    AND lower(btrim(c.new_name)) = $3))
+               ORDER BY document_date DESC, d.id
+               LIMIT $4"#,
+            LATEST_NODES
+        );
+        let documents = sqlx::query_as::<_, EntityMention>(&query)
+            .bind(entity.user_id)
+            .bind(&entity.label)
+            .bind(&entity.normalized_name)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(documents)
+    }
+
+    /// Ids of the user's entities with the given types and normalized names
+    pub async fn find_graph_entity_ids(
+        &self,
+        user_id: Uuid,
+        entities: &[(String, String)],
+    ) -> Result<HashMap<(String, String), Uuid>> {
+        if entities.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let (labels, names): (Vec<String>, Vec<String>) = entities.iter().cloned().unzip();
+        let rows = sqlx::query_as::<_, (Uuid, String, String)>(
+            r#"SELECT e.id, e.label, e.normalized_name
+               FROM graph_entities e
+               JOIN UNNEST($2::text[], $3::text[]) AS wanted(label, normalized_name)
+                 ON wanted.label = e.label AND wanted.normalized_name = e.normalized_name
+               WHERE e.user_id = $1"#
+        )
+        .bind(user_id)
+        .bind(&labels)
+        .bind(&names)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(id, label, name)| ((label, name), id)).collect())
+    }
+}
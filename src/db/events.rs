@@ -0,0 +1,151 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{EventType, StoredEvent};
+
+impl Database {
+    pub async fn create_event(
+        &self,
+        event_type: EventType,
+        user_id: Option<Uuid>,
+        resource_id: Option<Uuid>,
+        payload: &serde_json::Value,
+    ) -> Result<StoredEvent> {
+        let event = sqlx::query_as::<_, StoredEvent>(
+            r#"INSERT INTO events (event_type, schema_version, user_id, resource_id, payload)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING id, event_type, schema_version, user_id, resource_id, payload, occurred_at"#
+        )
+        .bind(event_type.as_str())
+        .bind(event_type.schema_version())
+        .bind(user_id)
+        .bind(resource_id)
+        .bind(payload)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(event)
+    }
+
+    /// Up to `limit` events in `[from, to)` in log order, after the `(occurred_at, id)`
+    /// position when one is given, optionally restricted to some types
+    pub async fn get_events_in_range(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        event_types: Option<&[EventType]>,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>> {
+        let type_filter: Option<Vec<String>> = event_types
+            .map(|types| types.iter().map(|t| t.as_str().to_string()).collect());
+
+        let events = sqlx::query_as::<_, StoredEvent>(
+            r#"SELECT id, event_type, schema_version, user_id, resource_id, payload, occurred_at
+               FROM events
+               WHERE occurred_at >= $1 AND occurred_at < $2
+                 AND ($3::timestamptz IS NULL OR (occurred_at, id) > ($3, $4))
+                 AND ($5::text[] IS NULL OR event_type = ANY($5))
+               ORDER BY occurred_at, id
+               LIMIT $6"#
+        )
+        .bind(from)
+        .bind(to)
+        .bind(after.map(|(occurred_at, _)| occurred_at))
+        .bind(after.map(|(_, id)| id))
+        .bind(type_filter)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    pub async fn get_event(&self, id: Uuid) -> Result<Option<StoredEvent>> {
+        let event = sqlx::query_as::<_, StoredEvent>(
+            "SELECT id, event_type, schema_version, user_id, resource_id, payload, occurred_at FROM events WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(event)
+    }
+
+    /// Stored events of a user that come after the `(occurred_at, id)` position in log
+    /// order, oldest first, optionally restricted to some types
+    pub async fn get_user_events_after(
+        &self,
+        user_id: Uuid,
+        after: (DateTime<Utc>, Uuid),
+        event_types: Option<&[EventType]>,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>> {
+        let type_filter: Option<Vec<String>> = event_types
+            .map(|types| types.iter().map(|t| t.as_str().to_string()).collect());
+
+        let events = sqlx::query_as::<_, StoredEvent>(
+            r#"SELECT id, event_type, schema_version, user_id, resource_id, payload, occurred_at
+               FROM events
+               WHERE user_id = $1
+                 AND (occurred_at, id) > ($2, $3)
+                 AND ($4::text[] IS NULL OR event_type = ANY($4))
+               ORDER BY occurred_at, id
+               LIMIT $5"#
+        )
+        .bind(user_id)
+        .bind(after.0)
+        .bind(after.1)
+        .bind(type_filter)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Remove events older than `retention_days`, with their webhook deliveries
+    pub async fn prune_events(&self, retention_days: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM events WHERE occurred_at < NOW() - INTERVAL '1 day' * $1")
+            .bind(retention_days)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Owner of a document and the ids of its labels, None when it does not exist
+    pub async fn get_document_owner_and_label_ids(&self, document_id: Uuid) -> Result<Option<(Uuid, Vec<Uuid>)>> {
+        let labels = sqlx::query_as::<_, (Uuid, Vec<Uuid>)>(
+            r#"SELECT d.user_id,
+                      COALESCE(array_agg(dl.label_id ORDER BY dl.label_id) FILTER (WHERE dl.label_id IS NOT NULL), '{}')
+               FROM documents d
+               LEFT JOIN document_labels dl ON dl.document_id = d.id
+               WHERE d.id = $1
+               GROUP BY d.user_id"#
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(labels)
+    }
+
+    /// Pending and processing OCR queue items per document owner, for owners that have any
+    pub async fn get_ocr_queue_counts_by_user(&self) -> Result<Vec<(Uuid, i64, i64)>> {
+        let counts = sqlx::query_as::<_, (Uuid, i64, i64)>(
+            r#"SELECT d.user_id,
+                      COUNT(*) FILTER (WHERE q.status = 'pending'),
+                      COUNT(*) FILTER (WHERE q.status = 'processing')
+               FROM ocr_queue q
+               JOIN documents d ON d.id = q.document_id
+               WHERE q.status IN ('pending', 'processing')
+               GROUP BY d.user_id"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(counts)
+    }
+}
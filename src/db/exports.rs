@@ -0,0 +1,493 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    Export, ExportError, ExportStatus, ExportedGraph, ExportedGraphEdge, ExportedGraphNode, ExportedLabel, ExportedUser,
+    Import, ImportStatus,
+};
+
+const EXPORT_COLUMNS: &str = "id, user_id, all_users, status, total_documents, processed_documents, \
+    failed_documents, errors, archive_size, error, created_at, started_at, completed_at";
+
+const IMPORT_COLUMNS: &str = "id, user_id, recreate_users, recreate_labels, status, total_documents, \
+    processed_documents, imported_documents, skipped_documents, failed_documents, created_users, \
+    created_labels, errors, error, created_at, started_at, completed_at";
+
+impl Database {
+    pub async fn create_export(&self, user_id: Uuid, all_users: bool, document_ids: &[Uuid]) -> Result<Export> {
+        let query = format!(
+            r#"INSERT INTO exports (user_id, all_users, document_ids, total_documents)
+               VALUES ($1, $2, $3, $4)
+               RETURNING {}"#,
+            EXPORT_COLUMNS
+        );
+        let export = sqlx::query_as::<_, Export>(&query)
+            .bind(user_id)
+            .bind(all_users)
+            .bind(document_ids)
+            .bind(document_ids.len() as i32)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(export)
+    }
+
+    pub async fn get_export(&self, id: Uuid) -> Result<Option<Export>> {
+        let query = format!("SELECT {} FROM exports WHERE id = $1", EXPORT_COLUMNS);
+        let export = sqlx::query_as::<_, Export>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(export)
+    }
+
+    /// The user's exports, newest first
+    pub async fn list_exports(&self, user_id: Uuid, limit: i64) -> Result<Vec<Export>> {
+        let query = format!(
+            "SELECT {} FROM exports WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2",
+            EXPORT_COLUMNS
+        );
+        let exports = sqlx::query_as::<_, Export>(&query)
+            .bind(user_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(exports)
+    }
+
+    /// Exports that were queued or running when the server stopped, oldest first.
+    /// Exports written for a backup are left to the backup scheduler.
+    pub async fn get_unfinished_exports(&self) -> Result<Vec<Export>> {
+        let query = format!(
+            r#"SELECT {} FROM exports
+               WHERE status IN ('queued', 'running')
+                 AND NOT EXISTS (SELECT 1 FROM backups WHERE backups.export_id = exports.id)
+               ORDER BY created_at"#,
+            EXPORT_COLUMNS
+        );
+        let exports = sqlx::query_as::<_, Export>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(exports)
+    }
+
+    pub async fn get_export_document_ids(&self, id: Uuid) -> Result<Vec<Uuid>> {
+        let document_ids = sqlx::query_scalar::<_, Vec<Uuid>>("SELECT document_ids FROM exports WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(document_ids.unwrap_or_default())
+    }
+
+    /// Mark the export as running. An archive cannot be continued, so the progress of
+    /// an interrupted run is cleared.
+    pub async fn start_export(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE exports
+               SET status = 'running', started_at = NOW(), processed_documents = 0,
+                   failed_documents = 0, errors = '[]'
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Add a batch's counts and failures to the export's progress
+    pub async fn update_export_progress(&self, id: Uuid, processed: i32, failed: i32, errors: &[ExportError]) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE exports
+               SET processed_documents = processed_documents + $2,
+                   failed_documents = failed_documents + $3,
+                   errors = errors || $4
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .bind(processed)
+        .bind(failed)
+        .bind(sqlx::types::Json(errors))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn finish_export(
+        &self,
+        id: Uuid,
+        status: ExportStatus,
+        archive_size: Option<i64>,
+        error: Option<&str>,
+    ) -> Result<Export> {
+        let query = format!(
+            r#"UPDATE exports
+               SET status = $2, archive_size = $3, error = $4, completed_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            EXPORT_COLUMNS
+        );
+        let export = sqlx::query_as::<_, Export>(&query)
+            .bind(id)
+            .bind(status.to_string())
+            .bind(archive_size)
+            .bind(error)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(export)
+    }
+
+    pub async fn delete_export(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM exports WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// IDs of the user's documents, or of every document when `user_id` is None, oldest
+    /// first. With `changed_since`, only documents created or updated after it.
+    pub async fn get_library_document_ids(
+        &self,
+        user_id: Option<Uuid>,
+        changed_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Uuid>> {
+        let document_ids = sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM documents
+               WHERE ($1::uuid IS NULL OR user_id = $1)
+                 AND ($2::timestamptz IS NULL OR updated_at > $2)
+               ORDER BY created_at, id"#
+        )
+        .bind(user_id)
+        .bind(changed_since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(document_ids)
+    }
+
+    pub async fn get_exported_users(&self, user_ids: &[Uuid]) -> Result<Vec<ExportedUser>> {
+        let users = sqlx::query_as::<_, ExportedUser>(
+            "SELECT id, username, email, role, created_at FROM users WHERE id = ANY($1) ORDER BY created_at"
+        )
+        .bind(user_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    /// The users' labels and the system labels, parents before their children
+    pub async fn get_exported_labels(&self, user_ids: &[Uuid]) -> Result<Vec<ExportedLabel>> {
+        let labels = sqlx::query_as::<_, ExportedLabel>(
+            r#"WITH RECURSIVE tree AS (
+                   SELECT l.id, 0 AS depth
+                   FROM labels l
+                   WHERE l.parent_id IS NULL AND (l.is_system = TRUE OR l.user_id = ANY($1))
+                   UNION ALL
+                   SELECT c.id, t.depth + 1
+                   FROM labels c
+                   JOIN tree t ON c.parent_id = t.id
+               )
+               SELECT l.id, l.user_id, l.parent_id, l.name, l.description, l.color,
+                      l.background_color, l.icon, l.is_system, l.created_at
+               FROM tree
+               JOIN labels l ON l.id = tree.id
+               ORDER BY tree.depth, l.name, l.id"#
+        )
+        .bind(user_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(labels)
+    }
+
+    /// The knowledge graph of a document as users see it, corrections included
+    pub async fn get_exported_graph(&self, document_id: Uuid) -> Result<ExportedGraph> {
+        let graph = self.get_document_graph(document_id, None).await?;
+        Ok(ExportedGraph {
+            nodes: graph
+                .nodes
+                .into_iter()
+                .map(|node| ExportedGraphNode { id: node.id, label: node.label, name: node.name, properties: node.properties })
+                .collect(),
+            edges: graph
+                .edges
+                .into_iter()
+                .map(|edge| ExportedGraphEdge {
+                    id: edge.id,
+                    source_node_id: edge.source,
+                    target_node_id: edge.target,
+                    relationship: edge.relationship,
+                    properties: edge.properties,
+                })
+                .collect(),
+        })
+    }
+
+    pub async fn create_import(&self, user_id: Uuid, recreate_users: bool, recreate_labels: bool) -> Result<Import> {
+        let query = format!(
+            r#"INSERT INTO imports (user_id, recreate_users, recreate_labels)
+               VALUES ($1, $2, $3)
+               RETURNING {}"#,
+            IMPORT_COLUMNS
+        );
+        let import = sqlx::query_as::<_, Import>(&query)
+            .bind(user_id)
+            .bind(recreate_users)
+            .bind(recreate_labels)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(import)
+    }
+
+    pub async fn get_import(&self, id: Uuid) -> Result<Option<Import>> {
+        let query = format!("SELECT {} FROM imports WHERE id = $1", IMPORT_COLUMNS);
+        let import = sqlx::query_as::<_, Import>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(import)
+    }
+
+    /// The user's imports, newest first
+    pub async fn list_imports(&self, user_id: Uuid, limit: i64) -> Result<Vec<Import>> {
+        let query = format!(
+            "SELECT {} FROM imports WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2",
+            IMPORT_COLUMNS
+        );
+        let imports = sqlx::query_as::<_, Import>(&query)
+            .bind(user_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(imports)
+    }
+
+    /// Imports that were queued or running when the server stopped, oldest first
+    pub async fn get_unfinished_imports(&self) -> Result<Vec<Import>> {
+        let query = format!(
+            "SELECT {} FROM imports WHERE status IN ('queued', 'running') ORDER BY created_at",
+            IMPORT_COLUMNS
+        );
+        let imports = sqlx::query_as::<_, Import>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(imports)
+    }
+
+    /// Mark the import as running with the manifest's document count. The progress of
+    /// an interrupted run is cleared, as the import starts over.
+    pub async fn start_import(&self, id: Uuid, total_documents: i32) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE imports
+               SET status = 'running', started_at = NOW(), total_documents = $2,
+                   processed_documents = 0, imported_documents = 0, skipped_documents = 0,
+                   failed_documents = 0, created_users = 0, created_labels = 0, errors = '[]'
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .bind(total_documents)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_import_created_counts(&self, id: Uuid, created_users: i32, created_labels: i32) -> Result<()> {
+        sqlx::query("UPDATE imports SET created_users = $2, created_labels = $3 WHERE id = $1")
+            .bind(id)
+            .bind(created_users)
+            .bind(created_labels)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Add a batch's counts and failures to the import's progress
+    pub async fn update_import_progress(
+        &self,
+        id: Uuid,
+        imported: i32,
+        skipped: i32,
+        failed: i32,
+        errors: &[ExportError],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE imports
+               SET processed_documents = processed_documents + $2 + $3 + $4,
+                   imported_documents = imported_documents + $2,
+                   skipped_documents = skipped_documents + $3,
+                   failed_documents = failed_documents + $4,
+                   errors = errors || $5
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .bind(imported)
+        .bind(skipped)
+        .bind(failed)
+        .bind(sqlx::types::Json(errors))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn finish_import(&self, id: Uuid, status: ImportStatus, error: Option<&str>) -> Result<Import> {
+        let query = format!(
+            r#"UPDATE imports
+               SET status = $2, error = $3, completed_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            IMPORT_COLUMNS
+        );
+        let import = sqlx::query_as::<_, Import>(&query)
+            .bind(id)
+            .bind(status.to_string())
+            .bind(error)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(import)
+    }
+
+    /// The user's label with this name, or the system label when `user_id` is None
+    pub async fn find_label_id_by_name(&self, user_id: Option<Uuid>, name: &str) -> Result<Option<Uuid>> {
+        let label_id = sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM labels
+               WHERE name = $2 AND (($1::uuid IS NULL AND is_system = TRUE) OR user_id = $1)
+               LIMIT 1"#
+        )
+        .bind(user_id)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(label_id)
+    }
+
+    /// Create a label from an export for `user_id`, or a system label when it is None.
+    /// The label keeps its exported ID unless another label has it.
+    pub async fn create_imported_label(
+        &self,
+        label: &ExportedLabel,
+        user_id: Option<Uuid>,
+        parent_id: Option<Uuid>,
+    ) -> Result<Uuid> {
+        let label_id = sqlx::query_scalar::<_, Uuid>(
+            r#"INSERT INTO labels (id, user_id, parent_id, name, description, color, background_color, icon, is_system)
+               SELECT CASE WHEN EXISTS (SELECT 1 FROM labels WHERE id = $1) THEN gen_random_uuid() ELSE $1 END,
+                      $2, $3, $4, $5, $6, $7, $8, $2 IS NULL
+               RETURNING id"#
+        )
+        .bind(label.id)
+        .bind(user_id)
+        .bind(parent_id)
+        .bind(&label.name)
+        .bind(&label.description)
+        .bind(&label.color)
+        .bind(&label.background_color)
+        .bind(&label.icon)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(label_id)
+    }
+
+    pub async fn document_id_exists(&self, document_id: Uuid) -> Result<bool> {
+        let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM documents WHERE id = $1)")
+            .bind(document_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(exists)
+    }
+
+    /// The user's document type with this ID, or else with this name
+    pub async fn find_document_type_id(&self, user_id: Uuid, id: Uuid, name: &str) -> Result<Option<Uuid>> {
+        let document_type_id = sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM document_types
+               WHERE user_id = $1 AND (id = $2 OR LOWER(name) = LOWER($3))
+               ORDER BY (id = $2) DESC
+               LIMIT 1"#
+        )
+        .bind(user_id)
+        .bind(id)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(document_type_id)
+    }
+
+    /// Recreate an exported knowledge graph for a document, with new node and edge IDs
+    pub async fn restore_document_graph(&self, document_id: Uuid, graph: &ExportedGraph) -> Result<()> {
+        if graph.nodes.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self.pool.begin().await?;
+
+        let version_id = sqlx::query_scalar::<_, Uuid>(
+            r#"INSERT INTO document_graph_versions (document_id, version_number, node_count, edge_count)
+               SELECT $1, COALESCE(MAX(version_number), 0) + 1, $2, $3
+               FROM document_graph_versions WHERE document_id = $1
+               RETURNING id"#
+        )
+        .bind(document_id)
+        .bind(graph.nodes.len() as i32)
+        .bind(graph.edges.len() as i32)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut node_ids = HashMap::new();
+        for node in &graph.nodes {
+            let node_id = sqlx::query_scalar::<_, Uuid>(
+                "INSERT INTO document_nodes (document_id, version_id, label, name, properties) VALUES ($1, $2, $3, $4, COALESCE($5, '{}'::jsonb)) RETURNING id"
+            )
+            .bind(document_id)
+            .bind(version_id)
+            .bind(&node.label)
+            .bind(&node.name)
+            .bind(&node.properties)
+            .fetch_one(&mut *tx)
+            .await?;
+            node_ids.insert(node.id, node_id);
+        }
+
+        for edge in &graph.edges {
+            let (Some(source_id), Some(target_id)) = (node_ids.get(&edge.source_node_id), node_ids.get(&edge.target_node_id)) else {
+                continue;
+            };
+            sqlx::query(
+                r#"INSERT INTO document_edges (document_id, version_id, source_node_id, target_node_id, relationship, properties)
+                   VALUES ($1, $2, $3, $4, $5, COALESCE($6, '{}'::jsonb))"#
+            )
+            .bind(document_id)
+            .bind(version_id)
+            .bind(source_id)
+            .bind(target_id)
+            .bind(&edge.relationship)
+            .bind(&edge.properties)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::documents::{map_row_to_document, DOCUMENT_FIELDS};
+use super::Database;
+use crate::models::{Document, EventType, ExternalSearchState, StoredEvent};
+
+const EXTERNAL_SEARCH_STATE_COLUMNS: &str = "engine, index_name, last_event_at, last_event_id, resync_status, \
+     resync_started_at, resync_completed_at, resync_documents, last_error, updated_at";
+
+impl Database {
+    pub async fn get_external_search_state(&self, engine: &str, index_name: &str) -> Result<Option<ExternalSearchState>> {
+        let query = format!(
+            "SELECT {} FROM external_search_state WHERE engine = $1 AND index_name = $2",
+            EXTERNAL_SEARCH_STATE_COLUMNS
+        );
+        let state = sqlx::query_as::<_, ExternalSearchState>(&query)
+            .bind(engine)
+            .bind(index_name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(state)
+    }
+
+    pub async fn save_external_search_position(
+        &self,
+        engine: &str,
+        index_name: &str,
+        position: (DateTime<Utc>, Uuid),
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO external_search_state (engine, index_name, last_event_at, last_event_id)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (engine, index_name) DO UPDATE
+               SET last_event_at = EXCLUDED.last_event_at, last_event_id = EXCLUDED.last_event_id, updated_at = NOW()"#
+        )
+        .bind(engine)
+        .bind(index_name)
+        .bind(position.0)
+        .bind(position.1)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark a full re-sync as running. Returns false when one already is.
+    pub async fn start_external_search_resync(&self, engine: &str, index_name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"INSERT INTO external_search_state (engine, index_name, resync_status, resync_started_at)
+               VALUES ($1, $2, 'running', NOW())
+               ON CONFLICT (engine, index_name) DO UPDATE
+               SET resync_status = 'running', resync_started_at = NOW(), resync_documents = 0,
+                   last_error = NULL, updated_at = NOW()
+               WHERE external_search_state.resync_status <> 'running'"#
+        )
+        .bind(engine)
+        .bind(index_name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record the end of a full re-sync; a successful one also moves the event log position
+    pub async fn finish_external_search_resync(
+        &self,
+        engine: &str,
+        index_name: &str,
+        documents: i64,
+        result: std::result::Result<Option<(DateTime<Utc>, Uuid)>, &str>,
+    ) -> Result<()> {
+        let (status, position, error) = match result {
+            Ok(position) => ("completed", position, None),
+            Err(error) => ("failed", None, Some(error)),
+        };
+        sqlx::query(
+            r#"UPDATE external_search_state
+               SET resync_status = $3, resync_completed_at = NOW(), resync_documents = $4, last_error = $5,
+                   last_event_at = CASE WHEN $3 = 'completed' THEN $6 ELSE last_event_at END,
+                   last_event_id = CASE WHEN $3 = 'completed' THEN $7 ELSE last_event_id END,
+                   updated_at = NOW()
+               WHERE engine = $1 AND index_name = $2"#
+        )
+        .bind(engine)
+        .bind(index_name)
+        .bind(status)
+        .bind(documents)
+        .bind(error)
+        .bind(position.map(|p| p.0))
+        .bind(position.map(|p| p.1))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// A re-sync interrupted by a shutdown is not running anymore
+    pub async fn reset_interrupted_external_search_resyncs(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"UPDATE external_search_state
+               SET resync_status = 'failed', last_error = 'Interrupted by a restart', updated_at = NOW()
+               WHERE resync_status = 'running'"#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Events of the given types after `position`, in log order. Events newer than
+    /// `settle_seconds` are left for later, since a transaction that began earlier may
+    /// still commit an event that sorts before them.
+    pub async fn get_events_after(
+        &self,
+        position: Option<(DateTime<Utc>, Uuid)>,
+        event_types: &[EventType],
+        settle_seconds: i32,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>> {
+        let types: Vec<String> = event_types.iter().map(|t| t.as_str().to_string()).collect();
+        let events = sqlx::query_as::<_, StoredEvent>(
+            r#"SELECT id, event_type, schema_version, user_id, resource_id, payload, occurred_at
+               FROM events
+               WHERE ($1::timestamptz IS NULL OR (occurred_at, id) > ($1, $2))
+                 AND occurred_at < NOW() - make_interval(secs => $3)
+                 AND event_type = ANY($4)
+               ORDER BY occurred_at, id
+               LIMIT $5"#
+        )
+        .bind(position.map(|p| p.0))
+        .bind(position.map(|p| p.1))
+        .bind(settle_seconds)
+        .bind(types)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Position of the newest event older than `settle_seconds`
+    pub async fn get_latest_event_position(&self, settle_seconds: i32) -> Result<Option<(DateTime<Utc>, Uuid)>> {
+        let position = sqlx::query_as::<_, (DateTime<Utc>, Uuid)>(
+            r#"SELECT occurred_at, id FROM events
+               WHERE occurred_at < NOW() - make_interval(secs => $1)
+               ORDER BY occurred_at DESC, id DESC LIMIT 1"#
+        )
+        .bind(settle_seconds)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(position)
+    }
+
+    /// Documents of any user by ID, for indexing and exports
+    pub async fn get_documents_by_ids(&self, document_ids: &[Uuid]) -> Result<Vec<Document>> {
+        let query = format!("SELECT {} FROM documents WHERE id = ANY($1)", DOCUMENT_FIELDS);
+        let rows = sqlx::query(&query)
+            .bind(document_ids)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(map_row_to_document).collect())
+    }
+
+    /// Every document in ID order, a page at a time, for a full re-sync
+    pub async fn get_documents_after_id(&self, after: Option<Uuid>, limit: i64) -> Result<Vec<Document>> {
+        let query = format!(
+            "SELECT {} FROM documents WHERE ($1::uuid IS NULL OR id > $1) ORDER BY id LIMIT $2",
+            DOCUMENT_FIELDS
+        );
+        let rows = sqlx::query(&query)
+            .bind(after)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(map_row_to_document).collect())
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{FailedDocument, FailedDocumentAttempt, FailureCategory};
+
+const FAILED_DOCUMENT_COLUMNS: &str = "id, user_id, filename, original_filename, original_path, file_path, \
+     file_size, file_hash, mime_type, content, COALESCE(tags, '{}') AS tags, ocr_text, ocr_confidence, \
+     ocr_word_count, ocr_processing_time_ms, failure_reason, failure_stage, existing_document_id, \
+     ingestion_source, error_message, retry_count, last_retry_at, created_at, updated_at";
+
+const FAILED_DOCUMENT_ATTEMPT_COLUMNS: &str = "id, failure_reason, failure_stage, error_message, ingestion_source, \
+     existing_document_id, retry_count, last_retry_at, requeued_at, requeued_document_id, created_at";
+
+impl Database {
+    /// A failed document record; `user_id` None looks across all users
+    pub async fn get_failed_document(&self, id: Uuid, user_id: Option<Uuid>) -> Result<Option<FailedDocument>> {
+        let query = format!(
+            "SELECT {} FROM failed_documents WHERE id = $1 AND ($2::uuid IS NULL OR user_id = $2)",
+            FAILED_DOCUMENT_COLUMNS
+        );
+        let failed = sqlx::query_as::<_, FailedDocument>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(failed)
+    }
+
+    /// Whether the failure was already requeued
+    pub async fn is_failed_document_requeued(&self, id: Uuid) -> Result<bool> {
+        let requeued: Option<bool> =
+            sqlx::query_scalar("SELECT requeued_at IS NOT NULL FROM failed_documents WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(requeued.unwrap_or(false))
+    }
+
+    /// Every failure of the same file as `failed`, oldest first: records with the same
+    /// content, and for OCR failures the other OCR failures of the same document
+    pub async fn get_failed_document_history(&self, failed: &FailedDocument) -> Result<Vec<FailedDocumentAttempt>> {
+        let ocr_document_id = failed.existing_document_id.filter(|_| failed.failure_stage == "ocr");
+        let query = format!(
+            r#"SELECT {} FROM failed_documents
+               WHERE user_id = $1
+                 AND (id = $2
+                      OR ($3::text IS NOT NULL AND file_hash = $3)
+                      OR ($4::uuid IS NOT NULL AND failure_stage = 'ocr' AND existing_document_id = $4))
+               ORDER BY created_at"#,
+            FAILED_DOCUMENT_ATTEMPT_COLUMNS
+        );
+
+        let mut attempts = sqlx::query_as::<_, FailedDocumentAttempt>(&query)
+            .bind(failed.user_id)
+            .bind(failed.id)
+            .bind(&failed.file_hash)
+            .bind(ocr_document_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        for attempt in &mut attempts {
+            attempt.category = FailureCategory::from_reason(&attempt.failure_reason).unwrap_or_default();
+        }
+        Ok(attempts)
+    }
+
+    /// Count a retry of the failure
+    pub async fn record_failed_document_retry(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE failed_documents
+               SET retry_count = COALESCE(retry_count, 0) + 1, last_retry_at = NOW(), updated_at = NOW()
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark the failure requeued, so it no longer shows as open. False when it
+    /// already was.
+    pub async fn mark_failed_document_requeued(&self, id: Uuid, document_id: Option<Uuid>) -> Result<bool> {
+        let updated = sqlx::query(
+            r#"UPDATE failed_documents
+               SET requeued_at = NOW(), requeued_document_id = $2, updated_at = NOW()
+               WHERE id = $1 AND requeued_at IS NULL"#
+        )
+        .bind(id)
+        .bind(document_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(updated > 0)
+    }
+}
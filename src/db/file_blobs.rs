@@ -0,0 +1,70 @@
+use anyhow::Result;
+
+use super::Database;
+use crate::models::FileBlob;
+
+const FILE_BLOB_COLUMNS: &str = "hash, storage_path, size, ref_count, created_at, updated_at";
+
+impl Database {
+    /// Add a reference to the blob with this content, if one is stored
+    pub async fn acquire_file_blob(&self, hash: &str) -> Result<Option<FileBlob>> {
+        let query = format!(
+            r#"UPDATE file_blobs SET ref_count = ref_count + 1, updated_at = NOW()
+               WHERE hash = $1
+               RETURNING {}"#,
+            FILE_BLOB_COLUMNS
+        );
+        let blob = sqlx::query_as::<_, FileBlob>(&query)
+            .bind(hash)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(blob)
+    }
+
+    /// Record a newly stored blob with one reference. When the same content was
+    /// recorded concurrently, that blob gets the reference and is returned instead.
+    pub async fn insert_file_blob(&self, hash: &str, storage_path: &str, size: i64) -> Result<FileBlob> {
+        let query = format!(
+            r#"INSERT INTO file_blobs (hash, storage_path, size)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (hash) DO UPDATE SET ref_count = file_blobs.ref_count + 1, updated_at = NOW()
+               RETURNING {}"#,
+            FILE_BLOB_COLUMNS
+        );
+        let blob = sqlx::query_as::<_, FileBlob>(&query)
+            .bind(hash)
+            .bind(storage_path)
+            .bind(size)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(blob)
+    }
+
+    /// Drop a reference to the blob at `storage_path`. Returns the references left,
+    /// or None when the path is not a blob.
+    pub async fn release_file_blob(&self, storage_path: &str) -> Result<Option<i32>> {
+        let remaining = sqlx::query_scalar::<_, i32>(
+            r#"UPDATE file_blobs SET ref_count = ref_count - 1, updated_at = NOW()
+               WHERE storage_path = $1 AND ref_count > 0
+               RETURNING ref_count"#,
+        )
+        .bind(storage_path)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(remaining)
+    }
+
+    /// Remove the row of a blob nothing references. False when it gained a
+    /// reference meanwhile, in which case the file must be kept.
+    pub async fn delete_unreferenced_file_blob(&self, storage_path: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM file_blobs WHERE storage_path = $1 AND ref_count = 0")
+            .bind(storage_path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
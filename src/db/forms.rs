@@ -0,0 +1,96 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{DocumentFormFields, FormTemplate, FormTemplateField};
+
+impl Database {
+    pub async fn get_form_templates(&self, user_id: Uuid) -> Result<Vec<FormTemplate>> {
+        let templates = sqlx::query_as::<_, FormTemplate>(
+            r#"SELECT id, user_id, name, fields, times_matched, created_at, updated_at
+               FROM form_templates
+               WHERE user_id = $1
+               ORDER BY times_matched DESC, created_at"#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(templates)
+    }
+
+    pub async fn create_form_template(&self, user_id: Uuid, name: &str, fields: &[FormTemplateField]) -> Result<FormTemplate> {
+        let template = sqlx::query_as::<_, FormTemplate>(
+            r#"INSERT INTO form_templates (user_id, name, fields, times_matched)
+               VALUES ($1, $2, $3, 1)
+               RETURNING id, user_id, name, fields, times_matched, created_at, updated_at"#
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(sqlx::types::Json(fields))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(template)
+    }
+
+    /// Record another document matching the template, replacing its field list
+    pub async fn record_form_template_match(&self, template_id: Uuid, fields: &[FormTemplateField]) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE form_templates
+               SET fields = $2, times_matched = times_matched + 1, updated_at = NOW()
+               WHERE id = $1"#
+        )
+        .bind(template_id)
+        .bind(sqlx::types::Json(fields))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_form_template(&self, template_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM form_templates WHERE id = $1 AND user_id = $2")
+            .bind(template_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn upsert_document_form_fields(
+        &self,
+        document_id: Uuid,
+        template_id: Option<Uuid>,
+        fields: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<DocumentFormFields> {
+        let row = sqlx::query_as::<_, DocumentFormFields>(
+            r#"INSERT INTO document_form_fields (document_id, template_id, fields)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (document_id)
+               DO UPDATE SET template_id = EXCLUDED.template_id, fields = EXCLUDED.fields, extracted_at = NOW()
+               RETURNING document_id, template_id, fields, extracted_at"#
+        )
+        .bind(document_id)
+        .bind(template_id)
+        .bind(sqlx::types::Json(fields))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn get_document_form_fields(&self, document_id: Uuid) -> Result<Option<DocumentFormFields>> {
+        let row = sqlx::query_as::<_, DocumentFormFields>(
+            r#"SELECT document_id, template_id, fields, extracted_at
+               FROM document_form_fields
+               WHERE document_id = $1"#
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+}
@@ -0,0 +1,223 @@
+use anyhow::Result;
+use sqlx::Row;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    CreateGuestPortalRequest, GuestPortal, GuestPortalDocument, UpdateGuestPortalRequest,
+    DEFAULT_GUEST_REQUESTS_PER_MINUTE,
+};
+
+const GUEST_PORTAL_FIELDS: &str = "id, slug, title, description, label_id, collection_id, allow_download, \
+    show_text, requests_per_minute, enabled, created_by, created_at, updated_at";
+
+/// The documents a portal exposes, as `portal_documents`, for the label `$1` or the
+/// collection `$2` and its subcollections. `UNION` so that a cycle ends the walk.
+const PORTAL_DOCUMENTS: &str = r#"
+    WITH RECURSIVE portal_collections AS (
+        SELECT id FROM collections WHERE id = $2
+        UNION
+        SELECT c.id FROM collections c JOIN portal_collections pc ON c.parent_id = pc.id
+    ),
+    portal_documents AS (
+        SELECT document_id FROM (
+            SELECT document_id FROM document_labels WHERE label_id = $1
+            UNION
+            SELECT document_id FROM collection_documents
+            WHERE collection_id IN (SELECT id FROM portal_collections)
+        ) linked
+        -- Embargoed documents stay off portals until they are released
+        WHERE NOT EXISTS (
+            SELECT 1 FROM document_embargoes de
+            WHERE de.document_id = linked.document_id AND de.released_at IS NULL
+              AND (de.embargoed_until IS NULL OR de.embargoed_until > NOW())
+        )
+    )
+"#;
+
+const PORTAL_DOCUMENT_FIELDS: &str = "d.id, d.original_filename AS filename, d.mime_type, d.file_size, d.created_at";
+
+impl Database {
+    pub async fn create_guest_portal(&self, request: &CreateGuestPortalRequest, created_by: Uuid) -> Result<GuestPortal> {
+        let query = format!(
+            r#"INSERT INTO guest_portals
+                   (slug, title, description, label_id, collection_id, allow_download, show_text,
+                    requests_per_minute, created_by)
+               VALUES ($1, $2, NULLIF(btrim($3), ''), $4, $5, $6, $7, $8, $9)
+               RETURNING {}"#,
+            GUEST_PORTAL_FIELDS
+        );
+        let portal = sqlx::query_as::<_, GuestPortal>(&query)
+            .bind(&request.slug)
+            .bind(request.title.trim())
+            .bind(&request.description)
+            .bind(request.label_id)
+            .bind(request.collection_id)
+            .bind(request.allow_download.unwrap_or(true))
+            .bind(request.show_text.unwrap_or(true))
+            .bind(request.requests_per_minute.unwrap_or(DEFAULT_GUEST_REQUESTS_PER_MINUTE))
+            .bind(created_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(portal)
+    }
+
+    pub async fn list_guest_portals(&self) -> Result<Vec<GuestPortal>> {
+        let query = format!("SELECT {} FROM guest_portals ORDER BY title", GUEST_PORTAL_FIELDS);
+        let portals = sqlx::query_as::<_, GuestPortal>(&query).fetch_all(&self.pool).await?;
+
+        Ok(portals)
+    }
+
+    pub async fn get_guest_portal(&self, id: Uuid) -> Result<Option<GuestPortal>> {
+        let query = format!("SELECT {} FROM guest_portals WHERE id = $1", GUEST_PORTAL_FIELDS);
+        let portal = sqlx::query_as::<_, GuestPortal>(&query).bind(id).fetch_optional(&self.pool).await?;
+
+        Ok(portal)
+    }
+
+    pub async fn get_guest_portal_by_slug(&self, slug: &str) -> Result<Option<GuestPortal>> {
+        let query = format!("SELECT {} FROM guest_portals WHERE slug = $1", GUEST_PORTAL_FIELDS);
+        let portal = sqlx::query_as::<_, GuestPortal>(&query).bind(slug).fetch_optional(&self.pool).await?;
+
+        Ok(portal)
+    }
+
+    pub async fn guest_portal_slug_taken(&self, slug: &str) -> Result<bool> {
+        let taken = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM guest_portals WHERE slug = $1)")
+            .bind(slug)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(taken)
+    }
+
+    /// Whether the label or collection a portal would expose exists
+    pub async fn guest_portal_scope_exists(&self, label_id: Option<Uuid>, collection_id: Option<Uuid>) -> Result<bool> {
+        let exists = sqlx::query_scalar(
+            r#"SELECT EXISTS(SELECT 1 FROM labels WHERE id = $1)
+                   OR EXISTS(SELECT 1 FROM collections WHERE id = $2)"#,
+        )
+        .bind(label_id)
+        .bind(collection_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Returns None when there is no such portal
+    pub async fn update_guest_portal(&self, id: Uuid, request: &UpdateGuestPortalRequest) -> Result<Option<GuestPortal>> {
+        let query = format!(
+            r#"UPDATE guest_portals
+               SET title = COALESCE(btrim($2), title),
+                   description = CASE WHEN $3::TEXT IS NULL THEN description ELSE NULLIF(btrim($3), '') END,
+                   allow_download = COALESCE($4, allow_download),
+                   show_text = COALESCE($5, show_text),
+                   requests_per_minute = COALESCE($6, requests_per_minute),
+                   enabled = COALESCE($7, enabled),
+                   updated_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            GUEST_PORTAL_FIELDS
+        );
+        let portal = sqlx::query_as::<_, GuestPortal>(&query)
+            .bind(id)
+            .bind(&request.title)
+            .bind(&request.description)
+            .bind(request.allow_download)
+            .bind(request.show_text)
+            .bind(request.requests_per_minute)
+            .bind(request.enabled)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(portal)
+    }
+
+    pub async fn delete_guest_portal(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM guest_portals WHERE id = $1").bind(id).execute(&self.pool).await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn count_guest_portal_documents(&self, portal: &GuestPortal) -> Result<i64> {
+        let query = format!("{} SELECT COUNT(*) FROM portal_documents", PORTAL_DOCUMENTS);
+        let count = sqlx::query_scalar(&query)
+            .bind(portal.label_id)
+            .bind(portal.collection_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Documents of the portal matching `text`, newest first, and how many match. The
+    /// text is searched only when the portal shows it.
+    pub async fn search_guest_portal_documents(
+        &self,
+        portal: &GuestPortal,
+        text: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<GuestPortalDocument>, i64)> {
+        let filter = r#"d.id IN (SELECT document_id FROM portal_documents)
+            AND ($3::TEXT IS NULL
+                 OR d.original_filename ILIKE '%' || $3 || '%'
+                 OR ($4 AND d.search_vector @@ readur_search_query($3)))"#;
+
+        let query = format!(
+            "{} SELECT {} FROM documents d WHERE {} ORDER BY d.created_at DESC, d.id LIMIT $5 OFFSET $6",
+            PORTAL_DOCUMENTS, PORTAL_DOCUMENT_FIELDS, filter
+        );
+        let documents = sqlx::query_as::<_, GuestPortalDocument>(&query)
+            .bind(portal.label_id)
+            .bind(portal.collection_id)
+            .bind(text)
+            .bind(portal.show_text)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let query = format!("{} SELECT COUNT(*) FROM documents d WHERE {}", PORTAL_DOCUMENTS, filter);
+        let total = sqlx::query_scalar(&query)
+            .bind(portal.label_id)
+            .bind(portal.collection_id)
+            .bind(text)
+            .bind(portal.show_text)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((documents, total))
+    }
+
+    /// A document of the portal with its OCR text, or None when the portal does not
+    /// expose it
+    pub async fn get_guest_portal_document(
+        &self,
+        portal: &GuestPortal,
+        document_id: Uuid,
+    ) -> Result<Option<(GuestPortalDocument, Option<String>, String)>> {
+        let query = format!(
+            r#"{} SELECT {}, d.ocr_text, d.file_path
+               FROM documents d
+               WHERE d.id = $3 AND d.id IN (SELECT document_id FROM portal_documents)"#,
+            PORTAL_DOCUMENTS, PORTAL_DOCUMENT_FIELDS
+        );
+        let row = sqlx::query(&query)
+            .bind(portal.label_id)
+            .bind(portal.collection_id)
+            .bind(document_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| {
+            let ocr_text: Option<String> = row.try_get("ocr_text")?;
+            let file_path: String = row.try_get("file_path")?;
+            Ok((sqlx::FromRow::from_row(&row)?, ocr_text, file_path))
+        })
+        .transpose()
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    CreateIngestionHookRequest, HookFailurePolicy, IngestionHook, IngestionHookStage, UpdateIngestionHookRequest,
+    DEFAULT_HOOK_TIMEOUT_SECONDS,
+};
+
+const INGESTION_HOOK_COLUMNS: &str = "id, name, stage, kind, target, timeout_seconds, on_failure, enabled, \
+    position, secret, last_run_at, last_error, created_by, created_at, updated_at";
+
+impl Database {
+    pub async fn create_ingestion_hook(
+        &self,
+        request: &CreateIngestionHookRequest,
+        secret: Option<&str>,
+        created_by: Uuid,
+    ) -> Result<IngestionHook> {
+        let query = format!(
+            r#"INSERT INTO ingestion_hooks
+                   (name, stage, kind, target, timeout_seconds, on_failure, enabled, position, secret, created_by)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+               RETURNING {}"#,
+            INGESTION_HOOK_COLUMNS
+        );
+        let hook = sqlx::query_as::<_, IngestionHook>(&query)
+            .bind(request.name.trim())
+            .bind(request.stage.to_string())
+            .bind(request.kind.to_string())
+            .bind(request.target.trim())
+            .bind(request.timeout_seconds.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECONDS))
+            .bind(request.on_failure.unwrap_or(HookFailurePolicy::Reject).to_string())
+            .bind(request.enabled.unwrap_or(true))
+            .bind(request.position.unwrap_or(0))
+            .bind(secret)
+            .bind(created_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(hook)
+    }
+
+    /// Every hook, by stage and in the order they run
+    pub async fn list_ingestion_hooks(&self) -> Result<Vec<IngestionHook>> {
+        let query = format!(
+            "SELECT {} FROM ingestion_hooks ORDER BY stage DESC, position, created_at",
+            INGESTION_HOOK_COLUMNS
+        );
+        let hooks = sqlx::query_as::<_, IngestionHook>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(hooks)
+    }
+
+    /// The enabled hooks of a stage in the order they run
+    pub async fn get_enabled_ingestion_hooks(&self, stage: IngestionHookStage) -> Result<Vec<IngestionHook>> {
+        let query = format!(
+            "SELECT {} FROM ingestion_hooks WHERE stage = $1 AND enabled ORDER BY position, created_at",
+            INGESTION_HOOK_COLUMNS
+        );
+        let hooks = sqlx::query_as::<_, IngestionHook>(&query)
+            .bind(stage.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(hooks)
+    }
+
+    pub async fn get_ingestion_hook(&self, id: Uuid) -> Result<Option<IngestionHook>> {
+        let query = format!("SELECT {} FROM ingestion_hooks WHERE id = $1", INGESTION_HOOK_COLUMNS);
+        let hook = sqlx::query_as::<_, IngestionHook>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(hook)
+    }
+
+    pub async fn update_ingestion_hook(
+        &self,
+        id: Uuid,
+        request: &UpdateIngestionHookRequest,
+    ) -> Result<Option<IngestionHook>> {
+        let query = format!(
+            r#"UPDATE ingestion_hooks
+               SET name = COALESCE($2, name),
+                   target = COALESCE($3, target),
+                   timeout_seconds = COALESCE($4, timeout_seconds),
+                   on_failure = COALESCE($5, on_failure),
+                   enabled = COALESCE($6, enabled),
+                   position = COALESCE($7, position),
+                   updated_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            INGESTION_HOOK_COLUMNS
+        );
+        let hook = sqlx::query_as::<_, IngestionHook>(&query)
+            .bind(id)
+            .bind(request.name.as_deref().map(str::trim))
+            .bind(request.target.as_deref().map(str::trim))
+            .bind(request.timeout_seconds)
+            .bind(request.on_failure.map(|policy| policy.to_string()))
+            .bind(request.enabled)
+            .bind(request.position)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(hook)
+    }
+
+    pub async fn delete_ingestion_hook(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM ingestion_hooks WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records a run of a hook; a run without error clears the last one
+    pub async fn record_ingestion_hook_run(&self, id: Uuid, error: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE ingestion_hooks SET last_run_at = NOW(), last_error = $2 WHERE id = $1")
+            .bind(id)
+            .bind(error)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{InstanceSettings, UpdateInstanceSettingsRequest};
+
+const INSTANCE_SETTINGS_COLUMNS: &str =
+    "name, logo_url, accent_color, login_message, default_language, updated_by, updated_at";
+
+impl Database {
+    pub async fn get_instance_settings(&self) -> Result<InstanceSettings> {
+        let query = format!("SELECT {} FROM instance_settings WHERE id", INSTANCE_SETTINGS_COLUMNS);
+        let settings = sqlx::query_as::<_, InstanceSettings>(&query)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(settings.unwrap_or_default())
+    }
+
+    /// Replace all instance settings
+    pub async fn set_instance_settings(
+        &self,
+        settings: &UpdateInstanceSettingsRequest,
+        updated_by: Uuid,
+    ) -> Result<InstanceSettings> {
+        let query = format!(
+            r#"INSERT INTO instance_settings
+                   (id, name, logo_url, accent_color, login_message, default_language, updated_by, updated_at)
+               VALUES (TRUE, $1, $2, $3, $4, $5, $6, NOW())
+               ON CONFLICT (id) DO UPDATE
+               SET name = EXCLUDED.name, logo_url = EXCLUDED.logo_url, accent_color = EXCLUDED.accent_color,
+                   login_message = EXCLUDED.login_message, default_language = EXCLUDED.default_language,
+                   updated_by = EXCLUDED.updated_by, updated_at = NOW()
+               RETURNING {}"#,
+            INSTANCE_SETTINGS_COLUMNS
+        );
+        let settings = sqlx::query_as::<_, InstanceSettings>(&query)
+            .bind(&settings.name)
+            .bind(&settings.logo_url)
+            .bind(&settings.accent_color)
+            .bind(&settings.login_message)
+            .bind(&settings.default_language)
+            .bind(updated_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(settings)
+    }
+}
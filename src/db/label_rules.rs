@@ -0,0 +1,128 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::documents::{map_row_to_document, DOCUMENT_FIELDS};
+use super::Database;
+use crate::models::{Document, LabelRule, SetLabelRuleRequest};
+
+const LABEL_RULE_COLUMNS: &str = "label_id, user_id, keywords, match_all_keywords, regex, sources, mime_types, \
+    case_sensitive, enabled, created_at, updated_at";
+
+impl Database {
+    pub async fn get_label_rule(&self, label_id: Uuid, user_id: Uuid) -> Result<Option<LabelRule>> {
+        let query = format!(
+            "SELECT {} FROM label_rules WHERE label_id = $1 AND user_id = $2",
+            LABEL_RULE_COLUMNS
+        );
+        let rule = sqlx::query_as::<_, LabelRule>(&query)
+            .bind(label_id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(rule)
+    }
+
+    /// The enabled rules of one user, or of every user when `None`
+    pub async fn get_enabled_label_rules(&self, user_id: Option<Uuid>) -> Result<Vec<LabelRule>> {
+        let query = format!(
+            "SELECT {} FROM label_rules WHERE enabled AND ($1::uuid IS NULL OR user_id = $1) ORDER BY user_id, label_id",
+            LABEL_RULE_COLUMNS
+        );
+        let rules = sqlx::query_as::<_, LabelRule>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rules)
+    }
+
+    /// Creates or replaces the rule of a label, which must be checked to be the user's
+    pub async fn set_label_rule(
+        &self,
+        label_id: Uuid,
+        user_id: Uuid,
+        rule: &SetLabelRuleRequest,
+    ) -> Result<LabelRule> {
+        let query = format!(
+            r#"INSERT INTO label_rules
+                   (label_id, user_id, keywords, match_all_keywords, regex, sources, mime_types, case_sensitive, enabled)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+               ON CONFLICT (label_id) DO UPDATE
+               SET keywords = EXCLUDED.keywords,
+                   match_all_keywords = EXCLUDED.match_all_keywords,
+                   regex = EXCLUDED.regex,
+                   sources = EXCLUDED.sources,
+                   mime_types = EXCLUDED.mime_types,
+                   case_sensitive = EXCLUDED.case_sensitive,
+                   enabled = EXCLUDED.enabled,
+                   updated_at = NOW()
+               RETURNING {}"#,
+            LABEL_RULE_COLUMNS
+        );
+        let rule = sqlx::query_as::<_, LabelRule>(&query)
+            .bind(label_id)
+            .bind(user_id)
+            .bind(&rule.keywords)
+            .bind(rule.match_all_keywords)
+            .bind(&rule.regex)
+            .bind(&rule.sources)
+            .bind(&rule.mime_types)
+            .bind(rule.case_sensitive)
+            .bind(rule.enabled.unwrap_or(true))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(rule)
+    }
+
+    pub async fn delete_label_rule(&self, label_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM label_rules WHERE label_id = $1 AND user_id = $2")
+            .bind(label_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Labels a document on behalf of a rule. Returns whether the label is new to it.
+    pub async fn apply_rule_label(&self, document_id: Uuid, label_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO document_labels (document_id, label_id, assigned_by) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"
+        )
+        .bind(document_id)
+        .bind(label_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// A batch of the documents of one user, or of every user when `None`, in id order
+    /// after `after`, for applying label rules to existing documents
+    pub async fn get_documents_for_label_rules(
+        &self,
+        user_id: Option<Uuid>,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Document>> {
+        let query = format!(
+            r#"SELECT {} FROM documents
+               WHERE ($1::uuid IS NULL OR user_id = $1)
+                 AND ($2::uuid IS NULL OR id > $2)
+               ORDER BY id
+               LIMIT $3"#,
+            DOCUMENT_FIELDS
+        );
+        let rows = sqlx::query(&query)
+            .bind(user_id)
+            .bind(after)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(map_row_to_document).collect())
+    }
+}
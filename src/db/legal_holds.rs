@@ -0,0 +1,167 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{LegalHold, ManifestEntry};
+
+const LEGAL_HOLD_FIELDS: &str = r#"
+    h.id, h.name, h.reason, h.created_by, h.created_at, h.released_at, h.released_by,
+    (SELECT COUNT(*) FROM legal_hold_documents d WHERE d.hold_id = h.id) AS document_count
+"#;
+
+impl Database {
+    pub async fn create_legal_hold(&self, name: &str, reason: Option<&str>, created_by: Uuid) -> Result<LegalHold> {
+        let id = sqlx::query_scalar::<_, Uuid>(
+            "INSERT INTO legal_holds (name, reason, created_by) VALUES ($1, $2, $3) RETURNING id",
+        )
+        .bind(name)
+        .bind(reason)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.get_legal_hold(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Legal hold {} vanished after it was created", id))
+    }
+
+    /// All holds, active ones first, newest first
+    pub async fn list_legal_holds(&self) -> Result<Vec<LegalHold>> {
+        let query = format!(
+            "SELECT {} FROM legal_holds h ORDER BY h.released_at IS NOT NULL, h.created_at DESC",
+            LEGAL_HOLD_FIELDS
+        );
+        Ok(sqlx::query_as::<_, LegalHold>(&query).fetch_all(&self.pool).await?)
+    }
+
+    pub async fn get_legal_hold(&self, hold_id: Uuid) -> Result<Option<LegalHold>> {
+        let query = format!("SELECT {} FROM legal_holds h WHERE h.id = $1", LEGAL_HOLD_FIELDS);
+        Ok(sqlx::query_as::<_, LegalHold>(&query).bind(hold_id).fetch_optional(&self.pool).await?)
+    }
+
+    /// Puts the documents on the hold. Returns the documents newly added and those
+    /// that were already on it; ids of documents that do not exist are in neither.
+    pub async fn add_documents_to_legal_hold(
+        &self,
+        hold_id: Uuid,
+        document_ids: &[Uuid],
+        added_by: Uuid,
+    ) -> Result<(Vec<Uuid>, Vec<Uuid>)> {
+        let already_held = sqlx::query_scalar::<_, Uuid>(
+            "SELECT document_id FROM legal_hold_documents WHERE hold_id = $1 AND document_id = ANY($2)",
+        )
+        .bind(hold_id)
+        .bind(document_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let added = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO legal_hold_documents (hold_id, document_id, added_by)
+            SELECT $1, id, $3 FROM documents WHERE id = ANY($2)
+            ON CONFLICT (hold_id, document_id) DO NOTHING
+            RETURNING document_id
+            "#,
+        )
+        .bind(hold_id)
+        .bind(document_ids)
+        .bind(added_by)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((added, already_held))
+    }
+
+    /// Takes a document off the hold. Returns false if it was not on it.
+    pub async fn remove_document_from_legal_hold(&self, hold_id: Uuid, document_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM legal_hold_documents WHERE hold_id = $1 AND document_id = $2")
+            .bind(hold_id)
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Lifts an active hold. Its documents stay listed on it as a record of what was
+    /// held. Returns false if the hold does not exist or was already released.
+    pub async fn release_legal_hold(&self, hold_id: Uuid, released_by: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE legal_holds SET released_at = NOW(), released_by = $2 WHERE id = $1 AND released_at IS NULL",
+        )
+        .bind(hold_id)
+        .bind(released_by)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Whether the document is on any active hold
+    pub async fn is_on_legal_hold(&self, document_id: Uuid) -> Result<bool> {
+        Ok(self.held_document_ids(&[document_id]).await?.contains(&document_id))
+    }
+
+    /// Those of the documents that are on an active hold
+    pub async fn held_document_ids(&self, document_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        let held = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT DISTINCT d.document_id
+            FROM legal_hold_documents d
+            JOIN legal_holds h ON h.id = d.hold_id
+            WHERE d.document_id = ANY($1) AND h.released_at IS NULL
+            "#,
+        )
+        .bind(document_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(held)
+    }
+
+    /// Every stored file of the documents on the hold, current contents and earlier
+    /// versions, ordered by document and then oldest version first
+    pub async fn get_legal_hold_manifest_entries(&self, hold_id: Uuid) -> Result<Vec<(ManifestEntry, String)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT doc.id AS document_id, NULL::INT AS version_number, doc.filename, doc.original_filename,
+                   doc.mime_type, doc.file_size, doc.file_hash, doc.file_path,
+                   doc.created_at AS stored_at, NULL::TIMESTAMPTZ AS superseded_at, d.added_at
+            FROM legal_hold_documents d
+            JOIN documents doc ON doc.id = d.document_id
+            WHERE d.hold_id = $1
+            UNION ALL
+            SELECT v.document_id, v.version_number, v.filename, doc.original_filename,
+                   v.mime_type, v.file_size, v.file_hash, v.file_path,
+                   v.created_at, v.superseded_at, d.added_at
+            FROM legal_hold_documents d
+            JOIN documents doc ON doc.id = d.document_id
+            JOIN document_versions v ON v.document_id = d.document_id
+            WHERE d.hold_id = $1
+            ORDER BY document_id, version_number NULLS LAST
+            "#,
+        )
+        .bind(hold_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let entry = ManifestEntry {
+                    document_id: row.get("document_id"),
+                    version_number: row.get("version_number"),
+                    filename: row.get("filename"),
+                    original_filename: row.get("original_filename"),
+                    mime_type: row.get("mime_type"),
+                    file_size: row.get("file_size"),
+                    file_hash: row.get("file_hash"),
+                    stored_at: row.get::<DateTime<Utc>, _>("stored_at"),
+                    superseded_at: row.get("superseded_at"),
+                    added_to_hold_at: row.get("added_at"),
+                    verified: None,
+                };
+                (entry, row.get("file_path"))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{LibraryFile, LibraryFolder, LibraryFolderEntry, LibraryGrouping};
+
+/// Year a document belongs to in the library: when the original file was created, or
+/// else when it was added
+const DOCUMENT_YEAR: &str = "EXTRACT(YEAR FROM COALESCE(d.original_created_at, d.created_at))::int";
+
+impl Database {
+    /// Folders under a grouping of the user's library; only those with documents
+    pub async fn get_library_folders(&self, user_id: Uuid, grouping: LibraryGrouping) -> Result<Vec<LibraryFolderEntry>> {
+        let query = match grouping {
+            LibraryGrouping::Labels => {
+                r#"SELECT l.id, l.name, MAX(d.updated_at) AS modified_at
+                   FROM labels l
+                   JOIN document_labels dl ON dl.label_id = l.id
+                   JOIN documents d ON d.id = dl.document_id
+                   WHERE d.user_id = $1
+                   GROUP BY l.id, l.name
+                   ORDER BY l.name"#
+                    .to_string()
+            }
+            LibraryGrouping::Correspondents => {
+                r#"SELECT c.id, c.name, MAX(d.updated_at) AS modified_at
+                   FROM correspondents c
+                   JOIN document_correspondents dc ON dc.correspondent_id = c.id
+                   JOIN documents d ON d.id = dc.document_id
+                   WHERE d.user_id = $1
+                   GROUP BY c.id, c.name
+                   ORDER BY c.name"#
+                    .to_string()
+            }
+            LibraryGrouping::Years => format!(
+                r#"SELECT {year} AS year, MAX(d.updated_at) AS modified_at
+                   FROM documents d
+                   WHERE d.user_id = $1
+                   GROUP BY 1
+                   ORDER BY 1 DESC"#,
+                year = DOCUMENT_YEAR
+            ),
+            LibraryGrouping::All => return Ok(Vec::new()),
+        };
+
+        let rows = sqlx::query(&query).bind(user_id).fetch_all(&self.pool).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let modified_at: DateTime<Utc> = row.get("modified_at");
+                match grouping {
+                    LibraryGrouping::Years => {
+                        let year: i32 = row.get("year");
+                        LibraryFolderEntry { folder: LibraryFolder::Year(year), name: year.to_string(), modified_at }
+                    }
+                    LibraryGrouping::Correspondents => LibraryFolderEntry {
+                        folder: LibraryFolder::Correspondent(row.get("id")),
+                        name: row.get("name"),
+                        modified_at,
+                    },
+                    LibraryGrouping::Labels | LibraryGrouping::All => LibraryFolderEntry {
+                        folder: LibraryFolder::Label(row.get("id")),
+                        name: row.get("name"),
+                        modified_at,
+                    },
+                }
+            })
+            .collect())
+    }
+
+    /// The user's documents in a library folder, by filename
+    pub async fn get_library_files(&self, user_id: Uuid, folder: LibraryFolder) -> Result<Vec<LibraryFile>> {
+        let filter = match folder {
+            LibraryFolder::Label(_) => {
+                "EXISTS (SELECT 1 FROM document_labels dl WHERE dl.document_id = d.id AND dl.label_id = $2)".to_string()
+            }
+            LibraryFolder::Correspondent(_) => {
+                "EXISTS (SELECT 1 FROM document_correspondents dc WHERE dc.document_id = d.id AND dc.correspondent_id = $2)"
+                    .to_string()
+            }
+            LibraryFolder::Year(_) => format!("{} = $2", DOCUMENT_YEAR),
+            LibraryFolder::All => "TRUE".to_string(),
+        };
+        let query = format!(
+            r#"SELECT d.id, d.original_filename, d.file_size, d.mime_type, d.file_hash, d.created_at, d.updated_at
+               FROM documents d
+               WHERE d.user_id = $1 AND {}
+               ORDER BY d.original_filename, d.id"#,
+            filter
+        );
+
+        let query = sqlx::query_as::<_, LibraryFile>(&query).bind(user_id);
+        let query = match folder {
+            LibraryFolder::Label(id) | LibraryFolder::Correspondent(id) => query.bind(id),
+            LibraryFolder::Year(year) => query.bind(year),
+            LibraryFolder::All => query,
+        };
+        Ok(query.fetch_all(&self.pool).await?)
+    }
+}
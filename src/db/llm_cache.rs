@@ -0,0 +1,43 @@
+use anyhow::Result;
+
+use super::Database;
+use crate::models::{InvalidateLlmCacheQuery, LlmCacheStats};
+
+impl Database {
+    /// Cached LLM answers per prompt version and model
+    pub async fn get_llm_cache_stats(&self) -> Result<Vec<LlmCacheStats>> {
+        let stats = sqlx::query_as::<_, LlmCacheStats>(
+            r#"SELECT task, prompt_version, model,
+                      COUNT(*) AS entries,
+                      COALESCE(SUM(hit_count), 0)::BIGINT AS hits,
+                      COALESCE(SUM(octet_length(response)), 0)::BIGINT AS response_bytes,
+                      MAX(last_hit_at) AS last_hit_at,
+                      MAX(created_at) AS newest_entry_at
+               FROM llm_response_cache
+               GROUP BY task, prompt_version, model
+               ORDER BY task, prompt_version DESC, model"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(stats)
+    }
+
+    /// Remove cached answers of a prompt, optionally of one version or model only.
+    /// Returns how many were removed.
+    pub async fn invalidate_llm_cache(&self, query: &InvalidateLlmCacheQuery) -> Result<u64> {
+        let result = sqlx::query(
+            r#"DELETE FROM llm_response_cache
+               WHERE task = $1
+                 AND ($2::int IS NULL OR prompt_version = $2)
+                 AND ($3::text IS NULL OR model = $3)"#
+        )
+        .bind(query.task.trim())
+        .bind(query.prompt_version)
+        .bind(query.model.as_deref())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
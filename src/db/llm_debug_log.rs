@@ -0,0 +1,81 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{LlmDebugExchange, LlmDebugRun, LlmDebugRunDetail, ListLlmDebugRunsQuery};
+
+const RUN_FIELDS: &str = r#"r.id, r.document_id, r.task, r.model, r.graph_version_id, r.error, r.redaction,
+    r.started_at, r.finished_at,
+    (SELECT COUNT(*) FROM llm_debug_exchanges e WHERE e.run_id = r.id) AS exchange_count"#;
+
+impl Database {
+    /// Logged analysis runs, newest first
+    pub async fn list_llm_debug_runs(&self, query: &ListLlmDebugRunsQuery) -> Result<Vec<LlmDebugRun>> {
+        let runs = sqlx::query_as::<_, LlmDebugRun>(&format!(
+            r#"SELECT {}
+               FROM llm_debug_runs r
+               WHERE ($1::uuid IS NULL OR r.document_id = $1)
+                 AND (NOT $2 OR r.error IS NOT NULL)
+               ORDER BY r.started_at DESC
+               LIMIT $3"#,
+            RUN_FIELDS
+        ))
+        .bind(query.document_id)
+        .bind(query.failed_only)
+        .bind(query.limit())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(runs)
+    }
+
+    pub async fn get_llm_debug_run(&self, run_id: Uuid) -> Result<Option<LlmDebugRunDetail>> {
+        let Some(run) = sqlx::query_as::<_, LlmDebugRun>(&format!(
+            "SELECT {} FROM llm_debug_runs r WHERE r.id = $1",
+            RUN_FIELDS
+        ))
+        .bind(run_id)
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let exchanges = sqlx::query_as::<_, LlmDebugExchange>(
+            r#"SELECT id, prompt_version, system_prompt, prompt, response, error, cached, duration_ms, created_at
+               FROM llm_debug_exchanges
+               WHERE run_id = $1
+               ORDER BY created_at"#,
+        )
+        .bind(run_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Some(LlmDebugRunDetail { run, exchanges }))
+    }
+
+    pub async fn count_llm_debug_runs(&self) -> Result<i64> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM llm_debug_runs")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count)
+    }
+
+    /// Remove logged runs, of one document or all. Returns how many were removed.
+    pub async fn clear_llm_debug_runs(&self, document_id: Option<Uuid>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM llm_debug_runs WHERE $1::uuid IS NULL OR document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Remove runs started more than `retention_days` ago
+    pub async fn prune_llm_debug_runs(&self, retention_days: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM llm_debug_runs WHERE started_at < NOW() - INTERVAL '1 day' * $1")
+            .bind(retention_days)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::VisionFallbackPage;
+
+impl Database {
+    pub async fn record_llm_usage(&self, user_id: Uuid, feature: &str, units: i32, document_id: Option<Uuid>) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO llm_usage (user_id, feature, units, document_id) VALUES ($1, $2, $3, $4)"
+        )
+        .bind(user_id)
+        .bind(feature)
+        .bind(units)
+        .bind(document_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Total units of `feature` consumed by the user since `since`
+    pub async fn get_llm_usage_since(&self, user_id: Uuid, feature: &str, since: DateTime<Utc>) -> Result<i64> {
+        let used = sqlx::query_scalar::<_, i64>(
+            r#"SELECT COALESCE(SUM(units), 0)::BIGINT
+               FROM llm_usage
+               WHERE user_id = $1 AND feature = $2 AND created_at >= $3"#
+        )
+        .bind(user_id)
+        .bind(feature)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(used)
+    }
+
+    pub async fn record_vision_fallback_page(
+        &self,
+        document_id: Uuid,
+        page_number: i32,
+        model: &str,
+        ocr_confidence: Option<f32>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO vision_fallback_pages (document_id, page_number, model, ocr_confidence)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (document_id, page_number)
+               DO UPDATE SET model = EXCLUDED.model, ocr_confidence = EXCLUDED.ocr_confidence, created_at = NOW()"#
+        )
+        .bind(document_id)
+        .bind(page_number)
+        .bind(model)
+        .bind(ocr_confidence)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_vision_fallback_pages(&self, document_id: Uuid) -> Result<Vec<VisionFallbackPage>> {
+        let pages = sqlx::query_as::<_, VisionFallbackPage>(
+            r#"SELECT id, document_id, page_number, model, ocr_confidence, created_at
+               FROM vision_fallback_pages
+               WHERE document_id = $1
+               ORDER BY page_number"#
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(pages)
+    }
+}
@@ -15,6 +15,95 @@ pub mod images;
 pub mod ignored_files;
 pub mod constraint_validation;
 pub mod ocr_retry;
+pub mod events;
+pub mod page_artifacts;
+pub mod llm_usage;
+pub mod llm_cache;
+pub mod llm_debug_log;
+pub mod outbound_limits;
+pub mod document_splits;
+pub mod forms;
+pub mod consistency;
+pub mod attachments;
+pub mod storage_migrations;
+pub mod storage_layout;
+pub mod s3_source_objects;
+pub mod webhooks;
+pub mod source_sync_state;
+pub mod encryption_keys;
+pub mod remote_source_files;
+pub mod webdav_outgoing_changes;
+pub mod physical_locations;
+pub mod external_search;
+pub mod client_sync;
+pub mod similar_documents;
+pub mod document_versions;
+pub mod retention_policies;
+pub mod audit_log;
+pub mod sharing;
+pub mod share_links;
+pub mod api_tokens;
+pub mod two_factor;
+pub mod passkeys;
+pub mod rate_limits;
+pub mod sessions;
+pub mod saved_searches;
+pub mod correspondents;
+pub mod document_types;
+pub mod label_rules;
+pub mod bulk_operations;
+pub mod notification_channels;
+pub mod exports;
+pub mod backups;
+pub mod resumable_uploads;
+pub mod file_blobs;
+pub mod quarantine;
+pub mod document_coordinates;
+pub mod source_schedules;
+pub mod source_sync_runs;
+pub mod source_filename_templates;
+pub mod workspaces;
+pub mod storage_quotas;
+pub mod annotations;
+pub mod pii_findings;
+pub mod calendar_feeds;
+pub mod reminders;
+pub mod workflow;
+pub mod legal_holds;
+pub mod search_analyzers;
+pub mod analysis_batches;
+pub mod document_graphs;
+pub mod entities;
+pub mod timeline_events;
+pub mod translations;
+pub mod document_audio;
+pub mod ingestion_hooks;
+pub mod failed_documents;
+pub mod library_dav;
+pub mod scan_bridges;
+pub mod scan_devices;
+pub mod ocr_comparisons;
+pub mod ocr_words;
+pub mod statistics;
+pub mod digests;
+pub mod document_relations;
+pub mod collections;
+pub mod omr;
+pub mod replica;
+pub mod thumbnail_jobs;
+pub mod storage_compression;
+pub mod worker_nodes;
+pub mod ocr_page_checkpoints;
+pub mod processing_usage;
+pub mod guest_portals;
+pub mod instance_settings;
+pub mod client_preferences;
+pub mod document_access;
+pub mod document_titles;
+pub mod document_embargoes;
+pub mod page_transforms;
+pub mod operation_controls;
+pub mod document_expirations;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatabasePoolHealth {
@@ -26,6 +115,8 @@ pub struct DatabasePoolHealth {
 #[derive(Clone)]
 pub struct Database {
     pub pool: PgPool,
+    /// Where heavy reads go when configured; see `read_with_fallback`
+    replica: Option<replica::ReadReplica>,
 }
 
 impl Database {
@@ -38,7 +129,7 @@ impl Database {
             .min_connections(5)                           // Maintain minimum connections
             .connect(database_url)
             .await?;
-        Ok(Self { pool })
+        Ok(Self { pool, replica: None })
     }
 
     pub async fn new_with_pool_config(database_url: &str, max_connections: u32, min_connections: u32) -> Result<Self> {
@@ -51,7 +142,7 @@ impl Database {
             .test_before_acquire(false)                  // Disable validation for speed
             .connect(database_url)
             .await?;
-        Ok(Self { pool })
+        Ok(Self { pool, replica: None })
     }
     
     pub fn get_pool(&self) -> &PgPool {
@@ -310,7 +401,7 @@ impl Database {
                 enable_image_preprocessing BOOLEAN DEFAULT TRUE,
                 search_results_per_page INT DEFAULT 25,
                 search_snippet_length INT DEFAULT 200,
-                fuzzy_search_threshold REAL DEFAULT 0.8,
+                fuzzy_search_threshold REAL DEFAULT 0.5,
                 retention_days INT,
                 enable_auto_cleanup BOOLEAN DEFAULT FALSE,
                 enable_compression BOOLEAN DEFAULT FALSE,
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use sqlx::Row;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    CreateNotificationChannelRequest, Notification, NotificationChannel, UpdateNotificationChannelRequest,
+    NOTIFICATION_TYPES,
+};
+
+const NOTIFICATION_CHANNEL_COLUMNS: &str =
+    "id, user_id, name, config, notification_types, enabled, last_sent_at, last_error, created_at, updated_at";
+
+impl Database {
+    pub async fn create_notification_channel(
+        &self,
+        user_id: Uuid,
+        request: &CreateNotificationChannelRequest,
+    ) -> Result<NotificationChannel> {
+        let notification_types = request
+            .notification_types
+            .clone()
+            .unwrap_or_else(|| NOTIFICATION_TYPES.iter().map(|t| t.to_string()).collect());
+        let query = format!(
+            r#"INSERT INTO notification_channels (user_id, name, config, notification_types, enabled)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING {}"#,
+            NOTIFICATION_CHANNEL_COLUMNS
+        );
+
+        let channel = sqlx::query_as::<_, NotificationChannel>(&query)
+            .bind(user_id)
+            .bind(request.name.trim())
+            .bind(sqlx::types::Json(&request.config))
+            .bind(&notification_types)
+            .bind(request.enabled.unwrap_or(true))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(channel)
+    }
+
+    pub async fn get_notification_channels(&self, user_id: Uuid) -> Result<Vec<NotificationChannel>> {
+        let query = format!(
+            "SELECT {} FROM notification_channels WHERE user_id = $1 ORDER BY created_at",
+            NOTIFICATION_CHANNEL_COLUMNS
+        );
+        let channels = sqlx::query_as::<_, NotificationChannel>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(channels)
+    }
+
+    pub async fn get_enabled_notification_channels(&self, user_id: Uuid) -> Result<Vec<NotificationChannel>> {
+        let query = format!(
+            "SELECT {} FROM notification_channels WHERE user_id = $1 AND enabled",
+            NOTIFICATION_CHANNEL_COLUMNS
+        );
+        let channels = sqlx::query_as::<_, NotificationChannel>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(channels)
+    }
+
+    pub async fn get_notification_channel(&self, id: Uuid, user_id: Uuid) -> Result<Option<NotificationChannel>> {
+        let query = format!(
+            "SELECT {} FROM notification_channels WHERE id = $1 AND user_id = $2",
+            NOTIFICATION_CHANNEL_COLUMNS
+        );
+        let channel = sqlx::query_as::<_, NotificationChannel>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(channel)
+    }
+
+    pub async fn update_notification_channel(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        request: &UpdateNotificationChannelRequest,
+    ) -> Result<Option<NotificationChannel>> {
+        let query = format!(
+            r#"UPDATE notification_channels
+               SET name = COALESCE($3, name),
+                   config = COALESCE($4, config),
+                   notification_types = COALESCE($5, notification_types),
+                   enabled = COALESCE($6, enabled),
+                   updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            NOTIFICATION_CHANNEL_COLUMNS
+        );
+
+        let channel = sqlx::query_as::<_, NotificationChannel>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(request.name.as_deref().map(str::trim))
+            .bind(request.config.as_ref().map(sqlx::types::Json))
+            .bind(&request.notification_types)
+            .bind(request.enabled)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(channel)
+    }
+
+    pub async fn delete_notification_channel(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM notification_channels WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record the outcome of a send; `error` is None on success
+    pub async fn record_notification_channel_send(&self, id: Uuid, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE notification_channels
+               SET last_sent_at = CASE WHEN $2::text IS NULL THEN NOW() ELSE last_sent_at END,
+                   last_error = $2
+               WHERE id = $1"#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark up to `limit` notifications that were not sent to outbound channels yet as
+    /// sent and return them, oldest first. Concurrent callers never get the same one.
+    pub async fn claim_undispatched_notifications(&self, limit: i64) -> Result<Vec<Notification>> {
+        let rows = sqlx::query(
+            r#"UPDATE notifications
+               SET dispatched_at = NOW()
+               WHERE id IN (
+                   SELECT id FROM notifications
+                   WHERE dispatched_at IS NULL
+                   ORDER BY created_at
+                   LIMIT $1
+                   FOR UPDATE SKIP LOCKED
+               )
+               RETURNING id, user_id, notification_type, title, message, read, action_url, metadata, created_at"#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut notifications: Vec<Notification> = rows
+            .into_iter()
+            .map(|row| Notification {
+                id: row.get("id"),
+                user_id: row.get("user_id"),
+                notification_type: row.get("notification_type"),
+                title: row.get("title"),
+                message: row.get("message"),
+                read: row.get("read"),
+                action_url: row.get("action_url"),
+                metadata: row.get("metadata"),
+                created_at: row.get("created_at"),
+            })
+            .collect();
+        notifications.sort_by_key(|notification| notification.created_at);
+
+        Ok(notifications)
+    }
+}
@@ -1,8 +1,10 @@
 use anyhow::Result;
-use sqlx::Row;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, Row};
 use uuid::Uuid;
 
 use super::Database;
+use crate::utils::pagination::PageRequest;
 
 impl Database {
     pub async fn create_notification(&self, user_id: Uuid, notification: &crate::models::CreateNotification) -> Result<crate::models::Notification> {
@@ -50,22 +52,43 @@ impl Database {
         .fetch_all(&self.pool)
         .await?;
 
-        let mut notifications = Vec::new();
-        for row in rows {
-            notifications.push(crate::models::Notification {
-                id: row.get("id"),
-                user_id: row.get("user_id"),
-                notification_type: row.get("notification_type"),
-                title: row.get("title"),
-                message: row.get("message"),
-                read: row.get("read"),
-                action_url: row.get("action_url"),
-                metadata: row.get("metadata"),
-                created_at: row.get("created_at"),
-            });
-        }
-
-        Ok(notifications)
+        Ok(rows.iter().map(notification_from_row).collect())
+    }
+
+    /// A page of the user's notifications, newest first. Fetches `page.fetch_limit()`
+    /// rows, starting after the page's cursor or offset.
+    pub async fn get_user_notifications_page(
+        &self,
+        user_id: Uuid,
+        page: &PageRequest<DateTime<Utc>>,
+    ) -> Result<Vec<crate::models::Notification>> {
+        let (after_created_at, after_id) = page.after.as_ref().map(|after| (after.key, after.id)).unzip();
+        let rows = sqlx::query(
+            r#"SELECT id, user_id, notification_type, title, message, read, action_url, metadata, created_at
+               FROM notifications
+               WHERE user_id = $1
+                 AND ($2::timestamptz IS NULL OR (created_at, id) < ($2, $3))
+               ORDER BY created_at DESC, id DESC
+               LIMIT $4 OFFSET $5"#
+        )
+        .bind(user_id)
+        .bind(after_created_at)
+        .bind(after_id)
+        .bind(page.fetch_limit())
+        .bind(page.offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(notification_from_row).collect())
+    }
+
+    pub async fn count_user_notifications(&self, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
     }
 
     pub async fn get_unread_notification_count(&self, user_id: Uuid) -> Result<i64> {
@@ -115,4 +138,18 @@ impl Database {
             recent_notifications,
         })
     }
-}
\ No newline at end of file
+}
+
+fn notification_from_row(row: &PgRow) -> crate::models::Notification {
+    crate::models::Notification {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        notification_type: row.get("notification_type"),
+        title: row.get("title"),
+        message: row.get("message"),
+        read: row.get("read"),
+        action_url: row.get("action_url"),
+        metadata: row.get("metadata"),
+        created_at: row.get("created_at"),
+    }
+}
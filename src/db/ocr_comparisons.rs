@@ -0,0 +1,249 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{OcrComparison, OcrComparisonDocument, OcrComparisonStatus, OcrEngineConfig};
+
+const OCR_COMPARISON_COLUMNS: &str = "id, user_id, name, engine_a, engine_b, status, total_documents, \
+    processed_documents, error, created_at, started_at, completed_at";
+
+const OCR_COMPARISON_DOCUMENT_COLUMNS: &str = "document_id, position, document_class, ground_truth, \
+    text_a, confidence_a, duration_ms_a, error_a, character_accuracy_a, word_accuracy_a, \
+    text_b, confidence_b, duration_ms_b, error_b, character_accuracy_b, word_accuracy_b, \
+    agreement, processed_at";
+
+impl Database {
+    /// A random sample of everyone's PDFs and images matching the filters
+    pub async fn find_ocr_comparison_sample(
+        &self,
+        label_id: Option<Uuid>,
+        document_type_id: Option<Uuid>,
+        mime_type: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Uuid>> {
+        let document_ids = sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT d.id FROM documents d
+               WHERE (d.mime_type = 'application/pdf' OR d.mime_type LIKE 'image/%')
+                 AND ($1::uuid IS NULL OR EXISTS (
+                     SELECT 1 FROM document_labels dl WHERE dl.document_id = d.id AND dl.label_id = $1))
+                 AND ($2::uuid IS NULL OR EXISTS (
+                     SELECT 1 FROM document_custom_fields cf
+                     WHERE cf.document_id = d.id AND cf.document_type_id = $2))
+                 AND ($3::text IS NULL OR d.mime_type = $3)
+               ORDER BY random()
+               LIMIT $4"#
+        )
+        .bind(label_id)
+        .bind(document_type_id)
+        .bind(mime_type)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(document_ids)
+    }
+
+    /// Create a comparison of the documents, in the given order; ids of documents that
+    /// do not exist are skipped. Each document is classed by its type, or else its MIME
+    /// type, and scored against `ground_truth` or else its hand-corrected OCR text.
+    pub async fn create_ocr_comparison(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        engine_a: &OcrEngineConfig,
+        engine_b: &OcrEngineConfig,
+        document_ids: &[Uuid],
+        ground_truth: &HashMap<Uuid, String>,
+    ) -> Result<OcrComparison> {
+        let mut tx = self.pool.begin().await?;
+
+        let comparison_id = sqlx::query_scalar::<_, Uuid>(
+            "INSERT INTO ocr_comparisons (user_id, name, engine_a, engine_b) VALUES ($1, $2, $3, $4) RETURNING id",
+        )
+        .bind(user_id)
+        .bind(name.trim())
+        .bind(sqlx::types::Json(engine_a))
+        .bind(sqlx::types::Json(engine_b))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let references: Vec<Option<String>> = document_ids.iter().map(|id| ground_truth.get(id).cloned()).collect();
+        let inserted = sqlx::query(
+            r#"INSERT INTO ocr_comparison_documents (comparison_id, document_id, position, document_class, ground_truth)
+               SELECT $1, d.id, s.position, COALESCE(dt.name, d.mime_type),
+                      COALESCE(s.ground_truth, CASE WHEN d.ocr_text_corrected_at IS NOT NULL THEN d.ocr_text END)
+               FROM UNNEST($2::uuid[], $3::text[]) WITH ORDINALITY AS s(document_id, ground_truth, position)
+               JOIN documents d ON d.id = s.document_id
+               LEFT JOIN document_custom_fields cf ON cf.document_id = d.id
+               LEFT JOIN document_types dt ON dt.id = cf.document_type_id
+               ON CONFLICT DO NOTHING"#
+        )
+        .bind(comparison_id)
+        .bind(document_ids)
+        .bind(&references)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let query = format!(
+            "UPDATE ocr_comparisons SET total_documents = $2 WHERE id = $1 RETURNING {}",
+            OCR_COMPARISON_COLUMNS
+        );
+        let comparison = sqlx::query_as::<_, OcrComparison>(&query)
+            .bind(comparison_id)
+            .bind(inserted as i32)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(comparison)
+    }
+
+    pub async fn get_ocr_comparison(&self, id: Uuid) -> Result<Option<OcrComparison>> {
+        let query = format!("SELECT {} FROM ocr_comparisons WHERE id = $1", OCR_COMPARISON_COLUMNS);
+        let comparison = sqlx::query_as::<_, OcrComparison>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(comparison)
+    }
+
+    /// Comparisons, newest first
+    pub async fn list_ocr_comparisons(&self, limit: i64) -> Result<Vec<OcrComparison>> {
+        let query = format!(
+            "SELECT {} FROM ocr_comparisons ORDER BY created_at DESC LIMIT $1",
+            OCR_COMPARISON_COLUMNS
+        );
+        let comparisons = sqlx::query_as::<_, OcrComparison>(&query)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(comparisons)
+    }
+
+    /// Comparisons that were queued or running when the server stopped, oldest first
+    pub async fn get_unfinished_ocr_comparisons(&self) -> Result<Vec<OcrComparison>> {
+        let query = format!(
+            "SELECT {} FROM ocr_comparisons WHERE status IN ('queued', 'running') ORDER BY created_at",
+            OCR_COMPARISON_COLUMNS
+        );
+        let comparisons = sqlx::query_as::<_, OcrComparison>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(comparisons)
+    }
+
+    pub async fn get_ocr_comparison_documents(&self, id: Uuid) -> Result<Vec<OcrComparisonDocument>> {
+        let query = format!(
+            "SELECT {} FROM ocr_comparison_documents WHERE comparison_id = $1 ORDER BY position",
+            OCR_COMPARISON_DOCUMENT_COLUMNS
+        );
+        let documents = sqlx::query_as::<_, OcrComparisonDocument>(&query)
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(documents)
+    }
+
+    /// The next document both engines still have to read, in sample order
+    pub async fn get_next_ocr_comparison_document(&self, id: Uuid) -> Result<Option<OcrComparisonDocument>> {
+        let query = format!(
+            r#"SELECT {} FROM ocr_comparison_documents
+               WHERE comparison_id = $1 AND processed_at IS NULL
+               ORDER BY position LIMIT 1"#,
+            OCR_COMPARISON_DOCUMENT_COLUMNS
+        );
+        let document = sqlx::query_as::<_, OcrComparisonDocument>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(document)
+    }
+
+    pub async fn start_ocr_comparison(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE ocr_comparisons
+               SET status = 'running', started_at = COALESCE(started_at, NOW())
+               WHERE id = $1 AND status IN ('queued', 'running')"#
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store what the engines made of a document and count it as processed
+    pub async fn save_ocr_comparison_document(&self, id: Uuid, result: &OcrComparisonDocument) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let updated = sqlx::query(
+            r#"UPDATE ocr_comparison_documents
+               SET text_a = $3, confidence_a = $4, duration_ms_a = $5, error_a = $6,
+                   character_accuracy_a = $7, word_accuracy_a = $8,
+                   text_b = $9, confidence_b = $10, duration_ms_b = $11, error_b = $12,
+                   character_accuracy_b = $13, word_accuracy_b = $14,
+                   agreement = $15, processed_at = NOW()
+               WHERE comparison_id = $1 AND document_id = $2 AND processed_at IS NULL"#
+        )
+        .bind(id)
+        .bind(result.document_id)
+        .bind(&result.text_a)
+        .bind(result.confidence_a)
+        .bind(result.duration_ms_a)
+        .bind(&result.error_a)
+        .bind(result.character_accuracy_a)
+        .bind(result.word_accuracy_a)
+        .bind(&result.text_b)
+        .bind(result.confidence_b)
+        .bind(result.duration_ms_b)
+        .bind(&result.error_b)
+        .bind(result.character_accuracy_b)
+        .bind(result.word_accuracy_b)
+        .bind(result.agreement)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if updated > 0 {
+            sqlx::query("UPDATE ocr_comparisons SET processed_documents = processed_documents + 1 WHERE id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn finish_ocr_comparison(&self, id: Uuid, status: OcrComparisonStatus, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE ocr_comparisons
+               SET status = $2, error = $3, completed_at = NOW()
+               WHERE id = $1 AND status IN ('queued', 'running')"#
+        )
+        .bind(id)
+        .bind(status.to_string())
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete a comparison with its results; a running one stops after its current
+    /// document. Returns false when there is no such comparison.
+    pub async fn delete_ocr_comparison(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM ocr_comparisons WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+
+impl Database {
+    /// Text and confidence of the pages of a document already OCRed, by page number
+    pub async fn get_ocr_page_checkpoints(&self, document_id: Uuid) -> Result<HashMap<i32, (String, f32)>> {
+        let rows = sqlx::query_as::<_, (i32, String, f32)>(
+            "SELECT page_number, text, confidence FROM ocr_page_checkpoints WHERE document_id = $1",
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(page, text, confidence)| (page, (text, confidence))).collect())
+    }
+
+    pub async fn save_ocr_page_checkpoint(&self, document_id: Uuid, page_number: i32, text: &str, confidence: f32) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO ocr_page_checkpoints (document_id, page_number, text, confidence)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (document_id, page_number) DO UPDATE SET text = $3, confidence = $4, created_at = NOW()"#,
+        )
+        .bind(document_id)
+        .bind(page_number)
+        .bind(text)
+        .bind(confidence)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear_ocr_page_checkpoints(&self, document_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM ocr_page_checkpoints WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
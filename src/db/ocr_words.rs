@@ -0,0 +1,98 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{CreateOcrPageWords, LowConfidenceDocument, OcrPageWords, UserRole};
+
+impl Database {
+    /// Replace the recognized words of a document with a fresh analysis
+    pub async fn replace_ocr_page_words(&self, document_id: Uuid, pages: &[CreateOcrPageWords]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM ocr_page_words WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for page in pages {
+            sqlx::query(
+                r#"INSERT INTO ocr_page_words (document_id, page_number, page_width, page_height, words)
+                   VALUES ($1, $2, $3, $4, $5)"#
+            )
+            .bind(document_id)
+            .bind(page.page_number)
+            .bind(page.page_width as i32)
+            .bind(page.page_height as i32)
+            .bind(sqlx::types::Json(&page.words))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_ocr_page_words(&self, document_id: Uuid) -> Result<Vec<OcrPageWords>> {
+        let pages = sqlx::query_as::<_, OcrPageWords>(
+            r#"SELECT document_id, page_number, page_width, page_height, words, created_at
+               FROM ocr_page_words
+               WHERE document_id = $1
+               ORDER BY page_number"#
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(pages)
+    }
+
+    /// Forget the recognized words of the given pages, or of every page, once their
+    /// text has been corrected by hand
+    pub async fn delete_ocr_page_words(&self, document_id: Uuid, pages: Option<&[i32]>) -> Result<()> {
+        sqlx::query(
+            r#"DELETE FROM ocr_page_words
+               WHERE document_id = $1 AND ($2::int[] IS NULL OR page_number = ANY($2))"#
+        )
+        .bind(document_id)
+        .bind(pages)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Documents visible to the user with words below `threshold`, those with the
+    /// most such words first
+    pub async fn get_low_confidence_documents(
+        &self,
+        user_id: Uuid,
+        user_role: UserRole,
+        threshold: f32,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<LowConfidenceDocument>> {
+        let documents = sqlx::query_as::<_, LowConfidenceDocument>(
+            r#"SELECT d.id AS document_id, d.original_filename AS filename,
+                      COUNT(*) FILTER (WHERE (w.word->>'confidence')::real < $3) AS flagged_words,
+                      COUNT(*) AS total_words,
+                      MIN((w.word->>'confidence')::real) AS lowest_confidence
+               FROM ocr_page_words p
+               JOIN documents d ON d.id = p.document_id
+               CROSS JOIN LATERAL jsonb_array_elements(p.words) AS w(word)
+               WHERE ($1 OR d.user_id = $2)
+               GROUP BY d.id, d.original_filename
+               HAVING COUNT(*) FILTER (WHERE (w.word->>'confidence')::real < $3) > 0
+               ORDER BY flagged_words DESC, lowest_confidence, d.id
+               LIMIT $4 OFFSET $5"#
+        )
+        .bind(user_role == UserRole::Admin)
+        .bind(user_id)
+        .bind(threshold)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(documents)
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::Result;
+use serde_json::{Map, Value};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::documents::apply_shared_access_filter;
+use super::Database;
+use crate::models::{
+    CreateOmrTemplateRequest, OmrFieldCount, OmrRegionReading, OmrResult, OmrResultListItem, OmrTemplate,
+    SharePermission, UpdateOmrTemplateRequest, UserRole, DEFAULT_OMR_FILL_THRESHOLD,
+};
+
+const OMR_TEMPLATE_COLUMNS: &str =
+    "id, name, description, page, match_phrases, regions, fill_threshold, enabled, created_by, created_at, updated_at";
+
+impl Database {
+    pub async fn get_omr_templates(&self) -> Result<Vec<OmrTemplate>> {
+        let query = format!("SELECT {} FROM omr_templates ORDER BY LOWER(name)", OMR_TEMPLATE_COLUMNS);
+        let templates = sqlx::query_as::<_, OmrTemplate>(&query).fetch_all(&self.pool).await?;
+
+        Ok(templates)
+    }
+
+    /// Templates scanned documents are matched against
+    pub async fn get_enabled_omr_templates(&self) -> Result<Vec<OmrTemplate>> {
+        let query = format!("SELECT {} FROM omr_templates WHERE enabled ORDER BY created_at", OMR_TEMPLATE_COLUMNS);
+        let templates = sqlx::query_as::<_, OmrTemplate>(&query).fetch_all(&self.pool).await?;
+
+        Ok(templates)
+    }
+
+    pub async fn get_omr_template(&self, id: Uuid) -> Result<Option<OmrTemplate>> {
+        let query = format!("SELECT {} FROM omr_templates WHERE id = $1", OMR_TEMPLATE_COLUMNS);
+        let template = sqlx::query_as::<_, OmrTemplate>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(template)
+    }
+
+    pub async fn create_omr_template(&self, created_by: Uuid, request: &CreateOmrTemplateRequest) -> Result<OmrTemplate> {
+        let query = format!(
+            r#"INSERT INTO omr_templates (name, description, page, match_phrases, regions, fill_threshold, enabled, created_by)
+               VALUES (btrim($1), NULLIF(btrim($2), ''), $3, $4, $5, $6, $7, $8)
+               RETURNING {}"#,
+            OMR_TEMPLATE_COLUMNS
+        );
+        let template = sqlx::query_as::<_, OmrTemplate>(&query)
+            .bind(&request.name)
+            .bind(request.description.as_deref())
+            .bind(request.page.unwrap_or(1))
+            .bind(&request.match_phrases)
+            .bind(sqlx::types::Json(&request.regions))
+            .bind(request.fill_threshold.unwrap_or(DEFAULT_OMR_FILL_THRESHOLD))
+            .bind(request.enabled.unwrap_or(true))
+            .bind(created_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(template)
+    }
+
+    pub async fn update_omr_template(&self, id: Uuid, request: &UpdateOmrTemplateRequest) -> Result<Option<OmrTemplate>> {
+        let query = format!(
+            r#"UPDATE omr_templates
+               SET name = COALESCE(btrim($2), name),
+                   description = CASE WHEN $3::text IS NULL THEN description ELSE NULLIF(btrim($3), '') END,
+                   page = COALESCE($4, page),
+                   match_phrases = COALESCE($5, match_phrases),
+                   regions = COALESCE($6, regions),
+                   fill_threshold = COALESCE($7, fill_threshold),
+                   enabled = COALESCE($8, enabled),
+                   updated_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            OMR_TEMPLATE_COLUMNS
+        );
+        let template = sqlx::query_as::<_, OmrTemplate>(&query)
+            .bind(id)
+            .bind(request.name.as_deref())
+            .bind(request.description.as_deref())
+            .bind(request.page)
+            .bind(request.match_phrases.as_ref())
+            .bind(request.regions.as_ref().map(sqlx::types::Json))
+            .bind(request.fill_threshold)
+            .bind(request.enabled)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(template)
+    }
+
+    /// Delete a template along with the results read with it
+    pub async fn delete_omr_template(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM omr_templates WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Store what was read from a document, replacing an earlier result
+    pub async fn upsert_omr_result(
+        &self,
+        document_id: Uuid,
+        template_id: Uuid,
+        data: &Map<String, Value>,
+        readings: &[OmrRegionReading],
+    ) -> Result<OmrResult> {
+        let result = sqlx::query_as::<_, OmrResult>(
+            r#"INSERT INTO omr_results (document_id, template_id, data, readings)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (document_id)
+               DO UPDATE SET template_id = EXCLUDED.template_id, data = EXCLUDED.data,
+                             readings = EXCLUDED.readings, extracted_at = NOW()
+               RETURNING document_id, template_id, data, readings, extracted_at"#,
+        )
+        .bind(document_id)
+        .bind(template_id)
+        .bind(sqlx::types::Json(data))
+        .bind(sqlx::types::Json(readings))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn get_omr_result(&self, document_id: Uuid) -> Result<Option<OmrResult>> {
+        let result = sqlx::query_as::<_, OmrResult>(
+            r#"SELECT document_id, template_id, data, readings, extracted_at
+               FROM omr_results
+               WHERE document_id = $1"#,
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Results of a template on documents the user can see, newest first. With a filter,
+    /// only results whose field has the value, or a list of values containing it.
+    pub async fn get_omr_results(
+        &self,
+        template_id: Uuid,
+        user_id: Uuid,
+        user_role: UserRole,
+        filter: Option<(String, Value)>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<OmrResultListItem>> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT r.document_id, r.template_id, r.data, r.readings, r.extracted_at, d.original_filename AS filename \
+             FROM omr_results r \
+             JOIN documents d ON d.id = r.document_id \
+             WHERE r.template_id = ",
+        );
+        query.push_bind(template_id);
+        if let Some((field, value)) = filter {
+            // A JSON null is stored for empty fields, while a missing key means the
+            // region was not read
+            query.push(" AND (r.data -> ");
+            query.push_bind(field.clone());
+            query.push(" = ");
+            query.push_bind(sqlx::types::Json(value.clone()));
+            query.push(" OR r.data -> ");
+            query.push_bind(field);
+            query.push(" @> jsonb_build_array(");
+            query.push_bind(sqlx::types::Json(value));
+            query.push("))");
+        }
+        query.push(" AND d.id IN (SELECT id FROM documents WHERE 1=1");
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::View);
+        query.push(") ORDER BY r.extracted_at DESC LIMIT ");
+        query.push_bind(limit);
+        query.push(" OFFSET ");
+        query.push_bind(offset);
+
+        let results = query.build_query_as::<OmrResultListItem>().fetch_all(&self.pool).await?;
+        Ok(results)
+    }
+
+    /// How often each value of the template's checkbox and group fields occurs in the
+    /// results the user can see. Free text fields in `text_keys` are left out; the
+    /// options of groups with several marks are counted one by one.
+    pub async fn get_omr_summary(
+        &self,
+        template_id: Uuid,
+        user_id: Uuid,
+        user_role: UserRole,
+        text_keys: &[String],
+    ) -> Result<Vec<OmrFieldCount>> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT f.key AS field, v.value, COUNT(*) AS count \
+             FROM omr_results r \
+             CROSS JOIN LATERAL jsonb_each(r.data) AS f(key, value) \
+             CROSS JOIN LATERAL jsonb_array_elements( \
+                 CASE WHEN jsonb_typeof(f.value) = 'array' THEN f.value ELSE jsonb_build_array(f.value) END \
+             ) AS v(value) \
+             WHERE r.template_id = ",
+        );
+        query.push_bind(template_id);
+        query.push(" AND NOT (f.key = ANY(");
+        query.push_bind(text_keys.to_vec());
+        query.push(")) AND r.document_id IN (SELECT id FROM documents WHERE 1=1");
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::View);
+        query.push(") GROUP BY f.key, v.value ORDER BY f.key, count DESC");
+
+        let counts = query.build_query_as::<OmrFieldCount>().fetch_all(&self.pool).await?;
+        Ok(counts)
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{OperationControls, QueueKind};
+
+const OPERATION_CONTROLS_COLUMNS: &str = "ocr_paused, sync_paused, analysis_paused, maintenance_mode, \
+     maintenance_message, maintenance_started_at, updated_by, updated_at";
+
+impl Database {
+    pub async fn get_operation_controls(&self) -> Result<OperationControls> {
+        let query = format!("SELECT {} FROM operation_controls WHERE id", OPERATION_CONTROLS_COLUMNS);
+        let controls = sqlx::query_as::<_, OperationControls>(&query)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(controls.unwrap_or_default())
+    }
+
+    pub async fn set_queue_paused(&self, queue: QueueKind, paused: bool, updated_by: Uuid) -> Result<OperationControls> {
+        let column = match queue {
+            QueueKind::Ocr => "ocr_paused",
+            QueueKind::Sync => "sync_paused",
+            QueueKind::Analysis => "analysis_paused",
+        };
+        let query = format!(
+            r#"INSERT INTO operation_controls (id, {column}, updated_by, updated_at)
+               VALUES (TRUE, $1, $2, NOW())
+               ON CONFLICT (id) DO UPDATE
+               SET {column} = EXCLUDED.{column}, updated_by = EXCLUDED.updated_by, updated_at = NOW()
+               RETURNING {columns}"#,
+            column = column,
+            columns = OPERATION_CONTROLS_COLUMNS
+        );
+        let controls = sqlx::query_as::<_, OperationControls>(&query)
+            .bind(paused)
+            .bind(updated_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(controls)
+    }
+
+    /// Turn the maintenance mode on or off; it keeps the time it was first turned on
+    pub async fn set_maintenance_mode(
+        &self,
+        enabled: bool,
+        message: Option<&str>,
+        updated_by: Uuid,
+    ) -> Result<OperationControls> {
+        let query = format!(
+            r#"INSERT INTO operation_controls
+                   (id, maintenance_mode, maintenance_message, maintenance_started_at, updated_by, updated_at)
+               VALUES (TRUE, $1, $2, CASE WHEN $1 THEN NOW() END, $3, NOW())
+               ON CONFLICT (id) DO UPDATE
+               SET maintenance_mode = EXCLUDED.maintenance_mode,
+                   maintenance_message = EXCLUDED.maintenance_message,
+                   maintenance_started_at = CASE
+                       WHEN NOT EXCLUDED.maintenance_mode THEN NULL
+                       ELSE COALESCE(operation_controls.maintenance_started_at, NOW())
+                   END,
+                   updated_by = EXCLUDED.updated_by, updated_at = NOW()
+               RETURNING {}"#,
+            OPERATION_CONTROLS_COLUMNS
+        );
+        let controls = sqlx::query_as::<_, OperationControls>(&query)
+            .bind(enabled)
+            .bind(message)
+            .bind(updated_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(controls)
+    }
+}
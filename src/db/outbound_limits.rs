@@ -0,0 +1,49 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{OutboundLimit, OutboundLimitOverride, OutboundScope};
+
+impl Database {
+    pub async fn get_outbound_limit_overrides(&self) -> Result<Vec<OutboundLimitOverride>> {
+        let overrides = sqlx::query_as::<_, OutboundLimitOverride>(
+            "SELECT scope, max_concurrent, requests_per_minute, burst, updated_at FROM outbound_limits"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(overrides)
+    }
+
+    pub async fn set_outbound_limit_override(&self, scope: OutboundScope, limit: &OutboundLimit, updated_by: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO outbound_limits (scope, max_concurrent, requests_per_minute, burst, updated_by)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (scope)
+               DO UPDATE SET max_concurrent = EXCLUDED.max_concurrent,
+                             requests_per_minute = EXCLUDED.requests_per_minute,
+                             burst = EXCLUDED.burst,
+                             updated_by = EXCLUDED.updated_by,
+                             updated_at = NOW()"#
+        )
+        .bind(scope.to_string())
+        .bind(limit.max_concurrent)
+        .bind(limit.requests_per_minute)
+        .bind(limit.burst)
+        .bind(updated_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Go back to the limits from the environment; false when none were set
+    pub async fn delete_outbound_limit_override(&self, scope: OutboundScope) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM outbound_limits WHERE scope = $1")
+            .bind(scope.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{ArtifactKind, CreatePageArtifact, PageArtifact, UserRole};
+
+impl Database {
+    /// Replace all artifacts of a document with a freshly detected set
+    pub async fn replace_page_artifacts(&self, document_id: Uuid, artifacts: &[CreatePageArtifact]) -> Result<Vec<PageArtifact>> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM page_artifacts WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut created = Vec::with_capacity(artifacts.len());
+        for artifact in artifacts {
+            let row = sqlx::query_as::<_, PageArtifact>(
+                r#"INSERT INTO page_artifacts (document_id, page_number, kind, bbox, detection_score, ocr_text, description)
+                   VALUES ($1, $2, $3, $4, $5, $6, $7)
+                   RETURNING id, document_id, page_number, kind, bbox, detection_score, ocr_text, description, created_at"#
+            )
+            .bind(artifact.document_id)
+            .bind(artifact.page_number)
+            .bind(artifact.kind.to_string())
+            .bind(sqlx::types::Json(artifact.bbox))
+            .bind(artifact.detection_score)
+            .bind(&artifact.ocr_text)
+            .bind(&artifact.description)
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(row);
+        }
+
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    pub async fn get_page_artifacts(&self, document_id: Uuid) -> Result<Vec<PageArtifact>> {
+        let artifacts = sqlx::query_as::<_, PageArtifact>(
+            r#"SELECT id, document_id, page_number, kind, bbox, detection_score, ocr_text, description, created_at
+               FROM page_artifacts
+               WHERE document_id = $1
+               ORDER BY page_number, kind"#
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(artifacts)
+    }
+
+    /// Search artifacts visible to the user by kind and/or text
+    pub async fn search_page_artifacts(
+        &self,
+        user_id: Uuid,
+        user_role: UserRole,
+        kind: Option<ArtifactKind>,
+        query: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<PageArtifact>> {
+        let pattern = query
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(|q| format!("%{}%", q));
+
+        let artifacts = sqlx::query_as::<_, PageArtifact>(
+            r#"SELECT pa.id, pa.document_id, pa.page_number, pa.kind, pa.bbox, pa.detection_score,
+                      pa.ocr_text, pa.description, pa.created_at
+               FROM page_artifacts pa
+               JOIN documents d ON d.id = pa.document_id
+               WHERE ($1 OR d.user_id = $2)
+                 AND ($3::text IS NULL OR pa.kind = $3)
+                 AND ($4::text IS NULL OR pa.ocr_text ILIKE $4 OR pa.description ILIKE $4)
+               ORDER BY pa.created_at DESC
+               LIMIT $5 OFFSET $6"#
+        )
+        .bind(user_role == UserRole::Admin)
+        .bind(user_id)
+        .bind(kind.map(|k| k.to_string()))
+        .bind(pattern)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(artifacts)
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::PageTransform;
+
+impl Database {
+    pub async fn get_page_transforms(&self, document_id: Uuid) -> Result<Vec<PageTransform>> {
+        let transforms = sqlx::query_as::<_, PageTransform>(
+            "SELECT * FROM document_page_transforms WHERE document_id = $1 ORDER BY page_number",
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(transforms)
+    }
+
+    /// Record detected transforms, leaving pages that already have one as they are, so
+    /// a page rotated by hand is never overridden by detection
+    pub async fn record_detected_page_transforms(&self, transforms: &[PageTransform]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for transform in transforms {
+            sqlx::query(
+                r#"INSERT INTO document_page_transforms
+                       (document_id, page_number, rotation, skew_degrees, source, confidence)
+                   VALUES ($1, $2, $3, $4, 'auto', $5)
+                   ON CONFLICT (document_id, page_number) DO NOTHING"#,
+            )
+            .bind(transform.document_id)
+            .bind(transform.page_number)
+            .bind(transform.rotation)
+            .bind(transform.skew_degrees)
+            .bind(transform.confidence)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Save transforms as given, replacing those of the same pages
+    pub async fn set_page_transforms(&self, transforms: &[PageTransform]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for transform in transforms {
+            sqlx::query(
+                r#"INSERT INTO document_page_transforms
+                       (document_id, page_number, rotation, skew_degrees, source, confidence, updated_by)
+                   VALUES ($1, $2, $3, $4, $5, $6, $7)
+                   ON CONFLICT (document_id, page_number) DO UPDATE SET
+                       rotation = $3, skew_degrees = $4, source = $5, confidence = $6,
+                       updated_by = $7, updated_at = NOW()"#,
+            )
+            .bind(transform.document_id)
+            .bind(transform.page_number)
+            .bind(transform.rotation)
+            .bind(transform.skew_degrees)
+            .bind(&transform.source)
+            .bind(transform.confidence)
+            .bind(transform.updated_by)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Replace all transforms of a document, as when its pages are reordered
+    pub async fn replace_page_transforms(&self, document_id: Uuid, transforms: &[PageTransform]) -> Result<()> {
+        self.delete_page_transforms(document_id).await?;
+        self.set_page_transforms(transforms).await
+    }
+
+    pub async fn delete_page_transforms(&self, document_id: Uuid) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM document_page_transforms WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
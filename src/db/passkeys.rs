@@ -0,0 +1,138 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::UserPasskey;
+
+const PASSKEY_FIELDS: &str = "id, user_id, name, credential_id, passkey, last_used_at, created_at";
+
+impl Database {
+    pub async fn create_user_passkey(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        credential_id: &[u8],
+        passkey: &serde_json::Value,
+    ) -> Result<UserPasskey> {
+        let query = format!(
+            r#"INSERT INTO user_passkeys (user_id, name, credential_id, passkey)
+               VALUES ($1, $2, $3, $4)
+               RETURNING {}"#,
+            PASSKEY_FIELDS
+        );
+        let passkey = sqlx::query_as::<_, UserPasskey>(&query)
+            .bind(user_id)
+            .bind(name)
+            .bind(credential_id)
+            .bind(passkey)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(passkey)
+    }
+
+    pub async fn get_user_passkeys(&self, user_id: Uuid) -> Result<Vec<UserPasskey>> {
+        let query = format!(
+            "SELECT {} FROM user_passkeys WHERE user_id = $1 ORDER BY created_at",
+            PASSKEY_FIELDS
+        );
+        let passkeys = sqlx::query_as::<_, UserPasskey>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(passkeys)
+    }
+
+    pub async fn get_passkey_by_credential_id(&self, credential_id: &[u8]) -> Result<Option<UserPasskey>> {
+        let query = format!("SELECT {} FROM user_passkeys WHERE credential_id = $1", PASSKEY_FIELDS);
+        let passkey = sqlx::query_as::<_, UserPasskey>(&query)
+            .bind(credential_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(passkey)
+    }
+
+    /// Records a login with the passkey, storing its credential when the signature
+    /// counter or backup state changed
+    pub async fn touch_user_passkey(&self, id: Uuid, passkey: Option<&serde_json::Value>) -> Result<()> {
+        sqlx::query(
+            "UPDATE user_passkeys SET last_used_at = NOW(), passkey = COALESCE($2, passkey) WHERE id = $1"
+        )
+        .bind(id)
+        .bind(passkey)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn rename_user_passkey(&self, id: Uuid, user_id: Uuid, name: &str) -> Result<Option<UserPasskey>> {
+        let query = format!(
+            "UPDATE user_passkeys SET name = $3 WHERE id = $1 AND user_id = $2 RETURNING {}",
+            PASSKEY_FIELDS
+        );
+        let passkey = sqlx::query_as::<_, UserPasskey>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(passkey)
+    }
+
+    pub async fn delete_user_passkey(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM user_passkeys WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Stores the server side of a WebAuthn ceremony until the browser answers,
+    /// clearing out abandoned ones
+    pub async fn create_webauthn_ceremony(
+        &self,
+        user_id: Uuid,
+        kind: &str,
+        state: &serde_json::Value,
+        timeout_minutes: i32,
+    ) -> Result<Uuid> {
+        sqlx::query("DELETE FROM webauthn_ceremonies WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        let id = sqlx::query_scalar::<_, Uuid>(
+            r#"INSERT INTO webauthn_ceremonies (user_id, kind, state, expires_at)
+               VALUES ($1, $2, $3, NOW() + make_interval(mins => $4))
+               RETURNING id"#
+        )
+        .bind(user_id)
+        .bind(kind)
+        .bind(state)
+        .bind(timeout_minutes)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Removes and returns an unexpired ceremony's user and state; each works only once
+    pub async fn take_webauthn_ceremony(&self, id: Uuid, kind: &str) -> Result<Option<(Uuid, serde_json::Value)>> {
+        let ceremony = sqlx::query_as::<_, (Uuid, serde_json::Value)>(
+            r#"DELETE FROM webauthn_ceremonies
+               WHERE id = $1 AND kind = $2 AND expires_at > NOW()
+               RETURNING user_id, state"#
+        )
+        .bind(id)
+        .bind(kind)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(ceremony)
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::documents::{map_row_to_document, DOCUMENT_FIELDS};
+use super::Database;
+use crate::models::{
+    CreatePhysicalLocationRequest, Document, PhysicalLocation, RetentionDueDocument, UpdatePhysicalLocationRequest,
+};
+
+const PHYSICAL_LOCATION_COLUMNS: &str = "id, user_id, parent_id, kind, name, description, created_at, updated_at";
+
+/// Date a document's retention period runs from
+const DOCUMENT_DATE: &str = "COALESCE(d.original_created_at, d.created_at)";
+
+/// Documents assigned directly to one location
+#[derive(Debug, Clone, Default)]
+pub struct LocationDocumentStats {
+    pub document_count: i64,
+    pub total_size_bytes: i64,
+    pub oldest_document_date: Option<DateTime<Utc>>,
+    pub newest_document_date: Option<DateTime<Utc>>,
+    pub due_for_destruction: i64,
+}
+
+impl Database {
+    pub async fn create_physical_location(&self, user_id: Uuid, request: &CreatePhysicalLocationRequest) -> Result<PhysicalLocation> {
+        let query = format!(
+            r#"INSERT INTO physical_locations (user_id, parent_id, kind, name, description)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING {}"#,
+            PHYSICAL_LOCATION_COLUMNS
+        );
+
+        let location = sqlx::query_as::<_, PhysicalLocation>(&query)
+            .bind(user_id)
+            .bind(request.parent_id)
+            .bind(&request.kind)
+            .bind(request.name.trim())
+            .bind(&request.description)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(location)
+    }
+
+    pub async fn get_physical_locations(&self, user_id: Uuid) -> Result<Vec<PhysicalLocation>> {
+        let query = format!(
+            "SELECT {} FROM physical_locations WHERE user_id = $1 ORDER BY LOWER(name)",
+            PHYSICAL_LOCATION_COLUMNS
+        );
+        let locations = sqlx::query_as::<_, PhysicalLocation>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(locations)
+    }
+
+    pub async fn get_physical_location(&self, id: Uuid, user_id: Uuid) -> Result<Option<PhysicalLocation>> {
+        let query = format!(
+            "SELECT {} FROM physical_locations WHERE id = $1 AND user_id = $2",
+            PHYSICAL_LOCATION_COLUMNS
+        );
+        let location = sqlx::query_as::<_, PhysicalLocation>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(location)
+    }
+
+    pub async fn update_physical_location(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        request: &UpdatePhysicalLocationRequest,
+    ) -> Result<Option<PhysicalLocation>> {
+        let query = format!(
+            r#"UPDATE physical_locations
+               SET kind = COALESCE($3, kind),
+                   name = COALESCE($4, name),
+                   description = COALESCE($5, description),
+                   updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            PHYSICAL_LOCATION_COLUMNS
+        );
+        let location = sqlx::query_as::<_, PhysicalLocation>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(&request.kind)
+            .bind(request.name.as_deref().map(str::trim))
+            .bind(&request.description)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(location)
+    }
+
+    pub async fn has_physical_sublocations(&self, id: Uuid) -> Result<bool> {
+        let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM physical_locations WHERE parent_id = $1)")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(exists)
+    }
+
+    /// Deletes a location; its documents no longer have a physical location
+    pub async fn delete_physical_location(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM physical_locations WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Puts the user's documents in `location_id`, or clears their location when None.
+    /// Returns how many documents changed.
+    pub async fn assign_physical_location(&self, user_id: Uuid, document_ids: &[Uuid], location_id: Option<Uuid>) -> Result<i64> {
+        let result = match location_id {
+            Some(location_id) => {
+                sqlx::query(
+                    r#"INSERT INTO document_physical_locations (document_id, location_id)
+                       SELECT id, $3 FROM documents WHERE id = ANY($1) AND user_id = $2
+                       ON CONFLICT (document_id) DO UPDATE
+                       SET location_id = EXCLUDED.location_id, assigned_at = NOW()"#
+                )
+                .bind(document_ids)
+                .bind(user_id)
+                .bind(location_id)
+                .execute(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query(
+                    r#"DELETE FROM document_physical_locations
+                       WHERE document_id IN (SELECT id FROM documents WHERE id = ANY($1) AND user_id = $2)"#
+                )
+                .bind(document_ids)
+                .bind(user_id)
+                .execute(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    /// Documents kept in a location, optionally including every location inside it
+    pub async fn get_physical_location_documents(
+        &self,
+        user_id: Uuid,
+        location_id: Uuid,
+        include_sublocations: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Document>> {
+        let query = format!(
+            r#"WITH RECURSIVE locations AS (
+                   SELECT id FROM physical_locations WHERE id = $2 AND user_id = $1
+                   UNION ALL
+                   SELECT child.id FROM physical_locations child
+                   JOIN locations ON child.parent_id = locations.id
+                   WHERE $3
+               )
+               SELECT {} FROM documents
+               WHERE user_id = $1
+                 AND id IN (SELECT document_id FROM document_physical_locations WHERE location_id IN (SELECT id FROM locations))
+               ORDER BY COALESCE(original_created_at, created_at), original_filename
+               LIMIT $4 OFFSET $5"#,
+            DOCUMENT_FIELDS
+        );
+        let rows = sqlx::query(&query)
+            .bind(user_id)
+            .bind(location_id)
+            .bind(include_sublocations)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(map_row_to_document).collect())
+    }
+
+    /// Statistics of the documents assigned directly to each of the user's locations
+    pub async fn get_physical_location_document_stats(
+        &self,
+        user_id: Uuid,
+        retention_days: Option<i32>,
+    ) -> Result<Vec<(Uuid, LocationDocumentStats)>> {
+        let query = format!(
+            r#"SELECT dpl.location_id,
+                      COUNT(*) AS document_count,
+                      COALESCE(SUM(d.file_size), 0)::BIGINT AS total_size_bytes,
+                      MIN({date}) AS oldest_document_date,
+                      MAX({date}) AS newest_document_date,
+                      COUNT(*) FILTER (
+                          WHERE $2::INT IS NOT NULL AND {date} + make_interval(days => $2::INT) <= NOW()
+                      ) AS due_for_destruction
+               FROM document_physical_locations dpl
+               JOIN documents d ON d.id = dpl.document_id
+               WHERE d.user_id = $1
+               GROUP BY dpl.location_id"#,
+            date = DOCUMENT_DATE
+        );
+        let rows = sqlx::query_as::<_, (Uuid, i64, i64, Option<DateTime<Utc>>, Option<DateTime<Utc>>, i64)>(&query)
+            .bind(user_id)
+            .bind(retention_days)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(location_id, document_count, total_size_bytes, oldest, newest, due)| {
+                (location_id, LocationDocumentStats {
+                    document_count,
+                    total_size_bytes,
+                    oldest_document_date: oldest,
+                    newest_document_date: newest,
+                    due_for_destruction: due,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn count_documents_without_physical_location(&self, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"SELECT COUNT(*) FROM documents d
+               WHERE d.user_id = $1
+                 AND NOT EXISTS (SELECT 1 FROM document_physical_locations dpl WHERE dpl.document_id = d.id)"#
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Documents with a physical location that are past the retention period, oldest first
+    pub async fn get_retention_due_documents(&self, user_id: Uuid, retention_days: i32) -> Result<Vec<(Uuid, RetentionDueDocument)>> {
+        let query = format!(
+            r#"SELECT dpl.location_id,
+                      d.id AS document_id,
+                      d.original_filename,
+                      {date} AS document_date,
+                      {date} + make_interval(days => $2) AS destroy_after
+               FROM document_physical_locations dpl
+               JOIN documents d ON d.id = dpl.document_id
+               WHERE d.user_id = $1 AND {date} + make_interval(days => $2) <= NOW()
+               ORDER BY {date}, d.original_filename"#,
+            date = DOCUMENT_DATE
+        );
+        let rows = sqlx::query_as::<_, (Uuid, Uuid, String, DateTime<Utc>, DateTime<Utc>)>(&query)
+            .bind(user_id)
+            .bind(retention_days)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(location_id, document_id, original_filename, document_date, destroy_after)| {
+                (location_id, RetentionDueDocument {
+                    document_id,
+                    original_filename,
+                    document_date,
+                    destroy_after,
+                })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{NewPiiFinding, PiiFinding};
+
+impl Database {
+    /// Replace the personal data found in a document with the results of a new scan
+    pub async fn replace_pii_findings(
+        &self,
+        document_id: Uuid,
+        findings: &[NewPiiFinding],
+        llm_used: bool,
+    ) -> Result<Vec<PiiFinding>> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM document_pii_findings WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for finding in findings {
+            sqlx::query(
+                r#"INSERT INTO document_pii_findings
+                       (document_id, kind, text, start_offset, end_offset, page_number, detected_by)
+                   VALUES ($1, $2, $3, $4, $5, $6, $7)"#
+            )
+            .bind(document_id)
+            .bind(finding.kind)
+            .bind(&finding.text)
+            .bind(finding.start_offset)
+            .bind(finding.end_offset)
+            .bind(finding.page_number)
+            .bind(finding.detected_by)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            r#"INSERT INTO document_pii_scans (document_id, scanned_at, llm_used)
+               VALUES ($1, NOW(), $2)
+               ON CONFLICT (document_id) DO UPDATE SET scanned_at = NOW(), llm_used = EXCLUDED.llm_used"#
+        )
+        .bind(document_id)
+        .bind(llm_used)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        self.get_pii_findings(document_id).await
+    }
+
+    /// Personal data found in a document, in text order
+    pub async fn get_pii_findings(&self, document_id: Uuid) -> Result<Vec<PiiFinding>> {
+        let findings = sqlx::query_as::<_, PiiFinding>(
+            r#"SELECT id, document_id, kind, text, start_offset, end_offset, page_number, detected_by, created_at
+               FROM document_pii_findings
+               WHERE document_id = $1
+               ORDER BY start_offset"#
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(findings)
+    }
+
+    /// When the document was last scanned for personal data and whether the LLM helped
+    pub async fn get_pii_scan(&self, document_id: Uuid) -> Result<Option<(DateTime<Utc>, bool)>> {
+        let scan = sqlx::query_as::<_, (DateTime<Utc>, bool)>(
+            "SELECT scanned_at, llm_used FROM document_pii_scans WHERE document_id = $1"
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(scan)
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    DocumentProcessingCostEntry, DocumentProcessingCosts, ProcessingCostSort, ProcessingCostTotals,
+    UserProcessingCosts,
+};
+
+/// Bytes a document `d` takes in storage: its file, earlier versions and an original
+/// kept after recompression
+const STORAGE_BYTES: &str = r#"(d.file_size
+    + COALESCE((SELECT SUM(v.file_size) FROM document_versions v WHERE v.document_id = d.id), 0)
+    + COALESCE((SELECT c.file_size FROM compressed_originals c WHERE c.document_id = d.id AND c.purged_at IS NULL), 0)
+    )::BIGINT"#;
+
+/// Usage rows `u` summed up per document
+const USAGE_SUMS: &str = r#"COUNT(u.id) FILTER (WHERE u.kind = 'ocr') AS ocr_runs,
+    COALESCE(SUM(u.seconds) FILTER (WHERE u.kind = 'ocr'), 0) AS ocr_seconds,
+    COALESCE(SUM(u.pages), 0)::BIGINT AS ocr_pages,
+    COUNT(u.id) FILTER (WHERE u.kind = 'llm') AS llm_requests,
+    COALESCE(SUM(u.seconds) FILTER (WHERE u.kind = 'llm'), 0) AS llm_seconds,
+    COALESCE(SUM(u.prompt_tokens), 0)::BIGINT AS llm_prompt_tokens,
+    COALESCE(SUM(u.completion_tokens), 0)::BIGINT AS llm_completion_tokens,
+    SUM(u.cost) AS llm_cost"#;
+
+impl Database {
+    /// Record an OCR run of the document
+    pub async fn record_ocr_usage(&self, document_id: Uuid, seconds: f64, pages: i32) -> Result<()> {
+        sqlx::query("INSERT INTO document_processing_usage (document_id, kind, seconds, pages) VALUES ($1, 'ocr', $2, $3)")
+            .bind(document_id)
+            .bind(seconds)
+            .bind(pages)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// What processing the document took since it was uploaded
+    pub async fn get_document_processing_costs(&self, document_id: Uuid) -> Result<DocumentProcessingCosts> {
+        let costs = sqlx::query_as::<_, DocumentProcessingCosts>(&format!(
+            r#"SELECT {usage_sums}, {storage_bytes} AS storage_bytes
+               FROM documents d
+               LEFT JOIN document_processing_usage u ON u.document_id = d.id
+               WHERE d.id = $1
+               GROUP BY d.id"#,
+            usage_sums = USAGE_SUMS,
+            storage_bytes = STORAGE_BYTES,
+        ))
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(costs.unwrap_or_default())
+    }
+
+    /// Processing of the last `days` days: totals, the users whose documents took the
+    /// most OCR time, and the documents ranked by `sort_by`
+    pub async fn get_processing_cost_report(
+        &self,
+        days: i32,
+        sort_by: ProcessingCostSort,
+        limit: i64,
+    ) -> Result<(ProcessingCostTotals, Vec<UserProcessingCosts>, Vec<DocumentProcessingCostEntry>)> {
+        let period_usage = format!(
+            r#"WITH document_usage AS (
+                   SELECT d.id AS document_id, d.filename, d.mime_type, d.user_id,
+                          {usage_sums}, {storage_bytes} AS storage_bytes
+                   FROM document_processing_usage u
+                   JOIN documents d ON d.id = u.document_id
+                   WHERE u.created_at >= NOW() - make_interval(days => $1)
+                   GROUP BY d.id
+               )"#,
+            usage_sums = USAGE_SUMS,
+            storage_bytes = STORAGE_BYTES,
+        );
+
+        let totals = sqlx::query_as::<_, ProcessingCostTotals>(&format!(
+            r#"{period_usage}
+               SELECT COUNT(*) AS documents,
+                      COALESCE(SUM(ocr_runs), 0)::BIGINT AS ocr_runs,
+                      COALESCE(SUM(ocr_seconds), 0) AS ocr_seconds,
+                      COALESCE(SUM(ocr_pages), 0)::BIGINT AS ocr_pages,
+                      COALESCE(SUM(llm_requests), 0)::BIGINT AS llm_requests,
+                      COALESCE(SUM(llm_seconds), 0) AS llm_seconds,
+                      COALESCE(SUM(llm_prompt_tokens), 0)::BIGINT AS llm_prompt_tokens,
+                      COALESCE(SUM(llm_completion_tokens), 0)::BIGINT AS llm_completion_tokens,
+                      SUM(llm_cost) AS llm_cost,
+                      COALESCE(SUM(storage_bytes), 0)::BIGINT AS storage_bytes
+               FROM document_usage"#
+        ))
+        .bind(days)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let users = sqlx::query_as::<_, UserProcessingCosts>(&format!(
+            r#"{period_usage}
+               SELECT du.user_id, u.username, COUNT(*) AS documents,
+                      SUM(du.ocr_seconds) AS ocr_seconds,
+                      SUM(du.ocr_pages)::BIGINT AS ocr_pages,
+                      SUM(du.llm_prompt_tokens + du.llm_completion_tokens)::BIGINT AS llm_tokens,
+                      SUM(du.llm_cost) AS llm_cost
+               FROM document_usage du
+               JOIN users u ON u.id = du.user_id
+               GROUP BY du.user_id, u.username
+               ORDER BY SUM(du.ocr_seconds) DESC, u.username
+               LIMIT $2"#
+        ))
+        .bind(days)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let documents = sqlx::query_as::<_, DocumentProcessingCostEntry>(&format!(
+            r#"{period_usage}
+               SELECT du.document_id, du.filename, du.mime_type, u.username,
+                      du.ocr_runs, du.ocr_seconds, du.ocr_pages, du.llm_requests,
+                      du.llm_prompt_tokens, du.llm_completion_tokens, du.llm_cost, du.storage_bytes
+               FROM document_usage du
+               JOIN users u ON u.id = du.user_id
+               ORDER BY {order} DESC, du.document_id
+               LIMIT $2"#,
+            order = sort_by.column(),
+        ))
+        .bind(days)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((totals, users, documents))
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{ConverterQuarantineEntry, QuarantinedFile};
+
+const QUARANTINED_FILE_COLUMNS: &str = "id, user_id, filename, original_filename, file_path, file_size, \
+    file_hash, mime_type, signature, source_type, source_id, source_path, created_at";
+
+impl Database {
+    pub async fn create_quarantined_file(&self, file: &QuarantinedFile) -> Result<QuarantinedFile> {
+        let query = format!(
+            r#"INSERT INTO quarantined_files (id, user_id, filename, original_filename, file_path, file_size,
+                   file_hash, mime_type, signature, source_type, source_id, source_path, created_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+               RETURNING {}"#,
+            QUARANTINED_FILE_COLUMNS
+        );
+        let quarantined = sqlx::query_as::<_, QuarantinedFile>(&query)
+            .bind(file.id)
+            .bind(file.user_id)
+            .bind(&file.filename)
+            .bind(&file.original_filename)
+            .bind(&file.file_path)
+            .bind(file.file_size)
+            .bind(&file.file_hash)
+            .bind(&file.mime_type)
+            .bind(&file.signature)
+            .bind(&file.source_type)
+            .bind(file.source_id)
+            .bind(&file.source_path)
+            .bind(file.created_at)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(quarantined)
+    }
+
+    /// Quarantined files, newest first
+    pub async fn list_quarantined_files(&self, limit: i64, offset: i64) -> Result<Vec<QuarantinedFile>> {
+        let query = format!(
+            "SELECT {} FROM quarantined_files ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+            QUARANTINED_FILE_COLUMNS
+        );
+        let files = sqlx::query_as::<_, QuarantinedFile>(&query)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(files)
+    }
+
+    pub async fn get_quarantined_file(&self, id: Uuid) -> Result<Option<QuarantinedFile>> {
+        let query = format!("SELECT {} FROM quarantined_files WHERE id = $1", QUARANTINED_FILE_COLUMNS);
+        let file = sqlx::query_as::<_, QuarantinedFile>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(file)
+    }
+
+    /// Remove the record of a quarantined file, returning it so its file can be deleted
+    pub async fn delete_quarantined_file(&self, id: Uuid) -> Result<Option<QuarantinedFile>> {
+        let query = format!("DELETE FROM quarantined_files WHERE id = $1 RETURNING {}", QUARANTINED_FILE_COLUMNS);
+        let file = sqlx::query_as::<_, QuarantinedFile>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(file)
+    }
+
+    /// Count a kill of a converter on a content, quarantining it once it was killed
+    /// `quarantine_after` times
+    pub async fn record_converter_strike(&self, file_hash: &str, tool: &str, reason: &str, quarantine_after: i32) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO converter_quarantine (file_hash, tool, reason, quarantined_at)
+               VALUES ($1, $2, $3, CASE WHEN $4 <= 1 THEN NOW() END)
+               ON CONFLICT (file_hash) DO UPDATE SET
+                   tool = EXCLUDED.tool, reason = EXCLUDED.reason,
+                   strikes = converter_quarantine.strikes + 1, last_killed_at = NOW(),
+                   quarantined_at = COALESCE(
+                       converter_quarantine.quarantined_at,
+                       CASE WHEN converter_quarantine.strikes + 1 >= $4 THEN NOW() END
+                   )"#,
+        )
+        .bind(file_hash)
+        .bind(tool)
+        .bind(reason)
+        .bind(quarantine_after)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Hashes of the contents no converter may run on
+    pub async fn get_quarantined_converter_hashes(&self) -> Result<Vec<String>> {
+        let hashes = sqlx::query_scalar("SELECT file_hash FROM converter_quarantine WHERE quarantined_at IS NOT NULL")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(hashes)
+    }
+
+    /// Contents converters were killed on, most recent first, with the documents that
+    /// have them
+    pub async fn list_converter_quarantine(
+        &self,
+        include_strikes: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ConverterQuarantineEntry>> {
+        let entries = sqlx::query_as::<_, ConverterQuarantineEntry>(
+            r#"SELECT q.file_hash, q.tool, q.reason, q.strikes,
+                      ARRAY(SELECT d.original_filename FROM documents d
+                            WHERE d.file_hash = q.file_hash ORDER BY d.created_at LIMIT 10) AS documents,
+                      q.first_killed_at, q.last_killed_at, q.quarantined_at
+               FROM converter_quarantine q
+               WHERE $1 OR q.quarantined_at IS NOT NULL
+               ORDER BY q.last_killed_at DESC
+               LIMIT $2 OFFSET $3"#,
+        )
+        .bind(include_strikes)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Let converters run on a content again and forget its strikes. False when it
+    /// was never killed.
+    pub async fn release_converter_quarantine(&self, file_hash: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM converter_quarantine WHERE file_hash = $1")
+            .bind(file_hash)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+
+use super::Database;
+
+/// Tokens in a bucket after refilling for the time since it was last used
+const REFILLED: &str = "LEAST($2, rate_limit_buckets.tokens + EXTRACT(EPOCH FROM NOW() - rate_limit_buckets.updated_at)::float8 * $3)";
+
+impl Database {
+    /// Takes a token from the bucket under `key` if one is left, creating a full bucket
+    /// for new keys. Returns whether a token was taken and the tokens left.
+    pub async fn take_rate_limit_token(&self, key: &str, capacity: f64, per_second: f64) -> Result<(bool, f64)> {
+        let query = format!(
+            r#"INSERT INTO rate_limit_buckets (key, tokens, allowed, updated_at)
+               VALUES ($1, $2 - 1, TRUE, NOW())
+               ON CONFLICT (key) DO UPDATE
+               SET tokens = CASE WHEN {refilled} >= 1 THEN {refilled} - 1 ELSE {refilled} END,
+                   allowed = {refilled} >= 1,
+                   updated_at = NOW()
+               RETURNING allowed, tokens"#,
+            refilled = REFILLED
+        );
+        let result = sqlx::query_as::<_, (bool, f64)>(&query)
+            .bind(key)
+            .bind(capacity)
+            .bind(per_second)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Tokens in the bucket under `key` without taking one; a full bucket for new keys
+    pub async fn peek_rate_limit_tokens(&self, key: &str, capacity: f64, per_second: f64) -> Result<f64> {
+        let query = format!("SELECT {} FROM rate_limit_buckets WHERE key = $1", REFILLED);
+        let tokens = sqlx::query_scalar::<_, f64>(&query)
+            .bind(key)
+            .bind(capacity)
+            .bind(per_second)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(tokens.unwrap_or(capacity))
+    }
+
+    /// Removes buckets unused for longer than any of them takes to refill
+    pub async fn prune_rate_limit_buckets(&self, idle_seconds: f64) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM rate_limit_buckets WHERE updated_at < NOW() - make_interval(secs => $1)"
+        )
+        .bind(idle_seconds)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
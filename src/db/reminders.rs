@@ -0,0 +1,203 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{DocumentReminder, ReminderStatus};
+
+const REMINDER_SELECT: &str = r#"
+    SELECT r.id, r.document_id, d.original_filename AS document_filename, r.user_id, r.note, r.remind_at,
+           r.status, r.sent_at, r.completed_at, r.snooze_count, r.created_at, r.updated_at
+    FROM document_reminders r
+    JOIN documents d ON d.id = r.document_id
+"#;
+
+impl Database {
+    /// The user's reminders, soonest first
+    pub async fn get_user_reminders(&self, user_id: Uuid, status: Option<ReminderStatus>) -> Result<Vec<DocumentReminder>> {
+        let reminders = sqlx::query_as::<_, DocumentReminder>(&format!(
+            "{} WHERE r.user_id = $1 AND ($2::reminder_status IS NULL OR r.status = $2) ORDER BY r.remind_at",
+            REMINDER_SELECT
+        ))
+        .bind(user_id)
+        .bind(status)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(reminders)
+    }
+
+    /// The user's reminders on a document, soonest first
+    pub async fn get_document_reminders(&self, document_id: Uuid, user_id: Uuid) -> Result<Vec<DocumentReminder>> {
+        let reminders = sqlx::query_as::<_, DocumentReminder>(&format!(
+            "{} WHERE r.document_id = $1 AND r.user_id = $2 ORDER BY r.remind_at",
+            REMINDER_SELECT
+        ))
+        .bind(document_id)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(reminders)
+    }
+
+    pub async fn get_reminder(&self, id: Uuid, user_id: Uuid) -> Result<Option<DocumentReminder>> {
+        let reminder = sqlx::query_as::<_, DocumentReminder>(&format!(
+            "{} WHERE r.id = $1 AND r.user_id = $2",
+            REMINDER_SELECT
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(reminder)
+    }
+
+    pub async fn create_reminder(
+        &self,
+        document_id: Uuid,
+        user_id: Uuid,
+        remind_at: DateTime<Utc>,
+        note: Option<&str>,
+    ) -> Result<DocumentReminder> {
+        let id: Uuid = sqlx::query_scalar(
+            r#"INSERT INTO document_reminders (document_id, user_id, remind_at, note)
+               VALUES ($1, $2, $3, $4)
+               RETURNING id"#
+        )
+        .bind(document_id)
+        .bind(user_id)
+        .bind(remind_at)
+        .bind(note)
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.get_reminder(id, user_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Reminder {} disappeared after creation", id))
+    }
+
+    /// Change the time and note of a reminder. A new time makes it pending again; an
+    /// empty note removes the note.
+    pub async fn update_reminder(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        remind_at: Option<DateTime<Utc>>,
+        note: Option<&str>,
+    ) -> Result<Option<DocumentReminder>> {
+        let updated = sqlx::query(
+            r#"UPDATE document_reminders
+               SET remind_at = COALESCE($3, remind_at),
+                   status = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN status ELSE 'pending' END,
+                   sent_at = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN sent_at END,
+                   completed_at = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN completed_at END,
+                   note = CASE WHEN $4::TEXT IS NULL THEN note ELSE NULLIF(btrim($4), '') END,
+                   updated_at = NOW()
+               WHERE id = $1 AND user_id = $2"#
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(remind_at)
+        .bind(note)
+        .execute(&self.pool)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_reminder(id, user_id).await
+    }
+
+    /// Put a reminder off until `until`; it becomes pending again
+    pub async fn snooze_reminder(&self, id: Uuid, user_id: Uuid, until: DateTime<Utc>) -> Result<Option<DocumentReminder>> {
+        let updated = sqlx::query(
+            r#"UPDATE document_reminders
+               SET remind_at = $3, status = 'pending', sent_at = NULL, completed_at = NULL,
+                   snooze_count = snooze_count + 1, updated_at = NOW()
+               WHERE id = $1 AND user_id = $2"#
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(until)
+        .execute(&self.pool)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_reminder(id, user_id).await
+    }
+
+    pub async fn complete_reminder(&self, id: Uuid, user_id: Uuid) -> Result<Option<DocumentReminder>> {
+        let updated = sqlx::query(
+            r#"UPDATE document_reminders
+               SET status = 'completed', completed_at = COALESCE(completed_at, NOW()), updated_at = NOW()
+               WHERE id = $1 AND user_id = $2"#
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_reminder(id, user_id).await
+    }
+
+    pub async fn delete_reminder(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM document_reminders WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark up to `limit` due reminders as sent and return them, oldest first. Rows
+    /// another instance is claiming at the same time are skipped, so each reminder is
+    /// sent once.
+    pub async fn claim_due_reminders(&self, limit: i64) -> Result<Vec<DocumentReminder>> {
+        let reminders = sqlx::query_as::<_, DocumentReminder>(
+            r#"WITH due AS (
+                   UPDATE document_reminders
+                   SET status = 'sent', sent_at = NOW(), updated_at = NOW()
+                   WHERE id IN (
+                       SELECT id FROM document_reminders
+                       WHERE status = 'pending' AND remind_at <= NOW()
+                       ORDER BY remind_at
+                       LIMIT $1
+                       FOR UPDATE SKIP LOCKED
+                   )
+                   RETURNING *
+               )
+               SELECT due.id, due.document_id, d.original_filename AS document_filename, due.user_id, due.note,
+                      due.remind_at, due.status, due.sent_at, due.completed_at, due.snooze_count,
+                      due.created_at, due.updated_at
+               FROM due
+               JOIN documents d ON d.id = due.document_id
+               ORDER BY due.remind_at"#
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(reminders)
+    }
+
+    /// Undo the claim of a reminder whose notification could not be created, so the
+    /// next run tries again
+    pub async fn release_reminder(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE document_reminders SET status = 'pending', sent_at = NULL WHERE id = $1 AND status = 'sent'"
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
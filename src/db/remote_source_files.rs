@@ -0,0 +1,46 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::Database;
+
+impl Database {
+    /// Remote path to (size, modification time) of every file the source has kept on the server
+    pub async fn get_remote_source_files(&self, source_id: Uuid) -> Result<HashMap<String, (i64, Option<DateTime<Utc>>)>> {
+        let rows = sqlx::query_as::<_, (String, i64, Option<DateTime<Utc>>)>(
+            "SELECT remote_path, file_size, modified_at FROM remote_source_files WHERE source_id = $1"
+        )
+        .bind(source_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(path, size, modified_at)| (path, (size, modified_at)))
+            .collect())
+    }
+
+    pub async fn record_remote_source_file(
+        &self,
+        source_id: Uuid,
+        remote_path: &str,
+        file_size: i64,
+        modified_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO remote_source_files (source_id, remote_path, file_size, modified_at)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (source_id, remote_path) DO UPDATE
+               SET file_size = EXCLUDED.file_size, modified_at = EXCLUDED.modified_at, last_seen_at = NOW()"#
+        )
+        .bind(source_id)
+        .bind(remote_path)
+        .bind(file_size)
+        .bind(modified_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+//! Read-only Postgres replica that heavy search, listing and statistics queries are
+//! sent to, so they do not compete with writes and the OCR queue on the primary.
+//!
+//! Queries fall back to the primary while the replica is unreachable or lags behind by
+//! more than `DATABASE_REPLICA_MAX_LAG_SECONDS`, and go back to it once it has caught up.
+
+use anyhow::Result;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::Database;
+
+/// How often the replica's reachability and lag are checked
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Lag behind the primary up to which the replica serves reads by default
+const DEFAULT_MAX_LAG_SECONDS: f64 = 10.0;
+/// Conflict with recovery: the replica canceled the query to apply changes from the primary
+const SERIALIZATION_FAILURE: &str = "40001";
+
+#[derive(Clone)]
+pub struct ReadReplica {
+    pub pool: PgPool,
+    healthy: Arc<AtomicBool>,
+    max_lag_seconds: f64,
+}
+
+impl ReadReplica {
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    fn set_healthy(&self, healthy: bool, reason: &str) {
+        let was_healthy = self.healthy.swap(healthy, Ordering::Relaxed);
+        if was_healthy && !healthy {
+            warn!("Read replica unavailable, reading from the primary: {}", reason);
+        } else if !was_healthy && healthy {
+            info!("Read replica available again");
+        }
+    }
+
+    /// Seconds the replica is behind the primary; 0 when it has replayed everything it
+    /// received, so an idle primary does not look like lag
+    async fn lag_seconds(&self) -> Result<f64> {
+        let lag = sqlx::query_scalar::<_, Option<f64>>(
+            r#"SELECT CASE
+                   WHEN NOT pg_is_in_recovery() THEN 0
+                   WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+                   ELSE EXTRACT(EPOCH FROM NOW() - pg_last_xact_replay_timestamp())::float8
+               END"#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(lag.unwrap_or(0.0))
+    }
+
+    async fn check(&self) {
+        match tokio::time::timeout(Duration::from_secs(5), self.lag_seconds()).await {
+            Ok(Ok(lag)) if lag > self.max_lag_seconds => {
+                self.set_healthy(false, &format!("{:.0}s behind the primary", lag))
+            }
+            Ok(Ok(_)) => self.set_healthy(true, ""),
+            Ok(Err(e)) => self.set_healthy(false, &e.to_string()),
+            Err(_) => self.set_healthy(false, "health check timed out"),
+        }
+    }
+}
+
+/// Errors after which a read is worth repeating on the primary
+fn should_fall_back(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::Protocol(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => true,
+        sqlx::Error::Database(db) => db.code().as_deref() == Some(SERIALIZATION_FAILURE),
+        _ => false,
+    }
+}
+
+impl Database {
+    /// Send heavy reads to a replica as well. Connections are opened on first use, so an
+    /// unreachable replica does not stop startup.
+    pub fn with_read_replica(mut self, replica_url: &str, max_connections: u32) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(Duration::from_secs(5))
+            .idle_timeout(Duration::from_secs(600))
+            .max_lifetime(Duration::from_secs(1800))
+            .connect_lazy(replica_url)?;
+        let max_lag_seconds = std::env::var("DATABASE_REPLICA_MAX_LAG_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .unwrap_or(DEFAULT_MAX_LAG_SECONDS);
+
+        self.replica = Some(ReadReplica { pool, healthy: Arc::new(AtomicBool::new(true)), max_lag_seconds });
+        Ok(self)
+    }
+
+    pub fn read_replica(&self) -> Option<&ReadReplica> {
+        self.replica.as_ref()
+    }
+
+    /// Whether reads currently go to a replica
+    pub fn reads_from_replica(&self) -> bool {
+        self.replica.as_ref().is_some_and(ReadReplica::is_healthy)
+    }
+
+    /// Run a read-only query on the replica when it is available, and on the primary
+    /// otherwise or when the replica fails it. The query must not write: it may run twice.
+    pub async fn read_with_fallback<T, F, Fut>(&self, operation: F) -> Result<T>
+    where
+        F: Fn(PgPool) -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        if let Some(replica) = self.replica.as_ref().filter(|replica| replica.is_healthy()) {
+            match operation(replica.pool.clone()).await {
+                Ok(result) => return Ok(result),
+                Err(e) if should_fall_back(&e) => {
+                    if !matches!(e, sqlx::Error::Database(_)) {
+                        replica.set_healthy(false, &e.to_string());
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(operation(self.pool.clone()).await?)
+    }
+
+    /// Keep checking the replica, so reads move back to it once it is reachable and has
+    /// caught up
+    pub async fn run_replica_health_check(self) {
+        let Some(replica) = self.replica else {
+            return;
+        };
+        let mut interval = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            replica.check().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_fall_back_on_connection_errors_only() {
+        assert!(should_fall_back(&sqlx::Error::PoolTimedOut));
+        assert!(should_fall_back(&sqlx::Error::Io(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))));
+        assert!(!should_fall_back(&sqlx::Error::RowNotFound));
+        assert!(!should_fall_back(&sqlx::Error::ColumnNotFound("id".to_string())));
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::ResumableUpload;
+
+const RESUMABLE_UPLOAD_COLUMNS: &str = "id, user_id, filename, mime_type, upload_length, upload_offset, \
+    ocr_language, status, document_id, error, created_at, updated_at, expires_at";
+
+impl Database {
+    pub async fn create_resumable_upload(
+        &self,
+        user_id: Uuid,
+        filename: &str,
+        mime_type: &str,
+        upload_length: i64,
+        ocr_language: Option<&str>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<ResumableUpload> {
+        let query = format!(
+            r#"INSERT INTO resumable_uploads (user_id, filename, mime_type, upload_length, ocr_language, expires_at)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               RETURNING {}"#,
+            RESUMABLE_UPLOAD_COLUMNS
+        );
+        let upload = sqlx::query_as::<_, ResumableUpload>(&query)
+            .bind(user_id)
+            .bind(filename)
+            .bind(mime_type)
+            .bind(upload_length)
+            .bind(ocr_language)
+            .bind(expires_at)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(upload)
+    }
+
+    /// The user's upload, if it exists
+    pub async fn get_resumable_upload(&self, id: Uuid, user_id: Uuid) -> Result<Option<ResumableUpload>> {
+        let query = format!(
+            "SELECT {} FROM resumable_uploads WHERE id = $1 AND user_id = $2",
+            RESUMABLE_UPLOAD_COLUMNS
+        );
+        let upload = sqlx::query_as::<_, ResumableUpload>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(upload)
+    }
+
+    pub async fn update_resumable_upload_offset(
+        &self,
+        id: Uuid,
+        upload_offset: i64,
+        expires_at: DateTime<Utc>,
+    ) -> Result<ResumableUpload> {
+        let query = format!(
+            r#"UPDATE resumable_uploads SET upload_offset = $2, expires_at = $3, updated_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            RESUMABLE_UPLOAD_COLUMNS
+        );
+        let upload = sqlx::query_as::<_, ResumableUpload>(&query)
+            .bind(id)
+            .bind(upload_offset)
+            .bind(expires_at)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(upload)
+    }
+
+    pub async fn complete_resumable_upload(&self, id: Uuid, document_id: Uuid) -> Result<ResumableUpload> {
+        let query = format!(
+            r#"UPDATE resumable_uploads SET status = 'completed', document_id = $2, updated_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            RESUMABLE_UPLOAD_COLUMNS
+        );
+        let upload = sqlx::query_as::<_, ResumableUpload>(&query)
+            .bind(id)
+            .bind(document_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(upload)
+    }
+
+    pub async fn fail_resumable_upload(&self, id: Uuid, error: &str) -> Result<ResumableUpload> {
+        let query = format!(
+            r#"UPDATE resumable_uploads SET status = 'failed', error = $2, updated_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            RESUMABLE_UPLOAD_COLUMNS
+        );
+        let upload = sqlx::query_as::<_, ResumableUpload>(&query)
+            .bind(id)
+            .bind(error)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(upload)
+    }
+
+    pub async fn delete_resumable_upload(&self, id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM resumable_uploads WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Uploads past their expiry, oldest first
+    pub async fn get_expired_resumable_uploads(&self, limit: i64) -> Result<Vec<ResumableUpload>> {
+        let query = format!(
+            "SELECT {} FROM resumable_uploads WHERE expires_at < NOW() ORDER BY expires_at LIMIT $1",
+            RESUMABLE_UPLOAD_COLUMNS
+        );
+        let uploads = sqlx::query_as::<_, ResumableUpload>(&query)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(uploads)
+    }
+}
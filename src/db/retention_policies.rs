@@ -0,0 +1,307 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::documents::{map_row_to_document, DOCUMENT_FIELDS};
+use super::Database;
+use crate::models::{Document, RetentionActionRecord, RetentionPolicy, RetentionReview};
+
+const RETENTION_POLICY_COLUMNS: &str =
+    "id, user_id, name, label_id, mime_type, action, retain_days, grace_days, enabled, created_at, updated_at";
+
+/// Document `d` falls under policy `p` and is past its retention period, which runs
+/// from the original creation date when known, otherwise the upload date
+const POLICY_APPLIES: &str = r#"
+    d.user_id = p.user_id
+    AND (p.label_id IS NULL
+         OR EXISTS (SELECT 1 FROM document_labels dl WHERE dl.document_id = d.id AND dl.label_id = p.label_id))
+    AND (p.mime_type IS NULL
+         OR d.mime_type = p.mime_type
+         OR (p.mime_type LIKE '%/' AND d.mime_type LIKE p.mime_type || '%'))
+    AND COALESCE(d.original_created_at, d.created_at) < NOW() - make_interval(days => p.retain_days)
+"#;
+
+/// A review whose document is to be deleted now
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DueRetentionDeletion {
+    pub review_id: Uuid,
+    pub policy_id: Uuid,
+    pub policy_name: String,
+    pub document_id: Uuid,
+    pub user_id: Uuid,
+}
+
+impl Database {
+    /// Saves a new policy from `policy`; its id and timestamps are assigned here
+    pub async fn create_retention_policy(&self, policy: &RetentionPolicy) -> Result<RetentionPolicy> {
+        let query = format!(
+            r#"INSERT INTO retention_policies (user_id, name, label_id, mime_type, action, retain_days, grace_days, enabled)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+               RETURNING {}"#,
+            RETENTION_POLICY_COLUMNS
+        );
+        let created = sqlx::query_as::<_, RetentionPolicy>(&query)
+            .bind(policy.user_id)
+            .bind(policy.name.trim())
+            .bind(policy.label_id)
+            .bind(&policy.mime_type)
+            .bind(&policy.action)
+            .bind(policy.retain_days)
+            .bind(policy.grace_days)
+            .bind(policy.enabled)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(created)
+    }
+
+    pub async fn get_retention_policies(&self, user_id: Uuid) -> Result<Vec<RetentionPolicy>> {
+        let query = format!(
+            "SELECT {} FROM retention_policies WHERE user_id = $1 ORDER BY LOWER(name)",
+            RETENTION_POLICY_COLUMNS
+        );
+        let policies = sqlx::query_as::<_, RetentionPolicy>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(policies)
+    }
+
+    pub async fn get_retention_policy(&self, id: Uuid, user_id: Uuid) -> Result<Option<RetentionPolicy>> {
+        let query = format!(
+            "SELECT {} FROM retention_policies WHERE id = $1 AND user_id = $2",
+            RETENTION_POLICY_COLUMNS
+        );
+        let policy = sqlx::query_as::<_, RetentionPolicy>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(policy)
+    }
+
+    /// Enabled policies of every user
+    pub async fn get_enabled_retention_policies(&self) -> Result<Vec<RetentionPolicy>> {
+        let query = format!(
+            "SELECT {} FROM retention_policies WHERE enabled = TRUE ORDER BY created_at",
+            RETENTION_POLICY_COLUMNS
+        );
+        let policies = sqlx::query_as::<_, RetentionPolicy>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(policies)
+    }
+
+    /// Saves the editable fields of `policy`
+    pub async fn update_retention_policy(&self, policy: &RetentionPolicy) -> Result<Option<RetentionPolicy>> {
+        let query = format!(
+            r#"UPDATE retention_policies
+               SET name = $3, label_id = $4, mime_type = $5, action = $6,
+                   retain_days = $7, grace_days = $8, enabled = $9, updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            RETENTION_POLICY_COLUMNS
+        );
+        let updated = sqlx::query_as::<_, RetentionPolicy>(&query)
+            .bind(policy.id)
+            .bind(policy.user_id)
+            .bind(policy.name.trim())
+            .bind(policy.label_id)
+            .bind(&policy.mime_type)
+            .bind(&policy.action)
+            .bind(policy.retain_days)
+            .bind(policy.grace_days)
+            .bind(policy.enabled)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(updated)
+    }
+
+    /// Deletes a policy and its review queue entries; its action records are kept
+    pub async fn delete_retention_policy(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM retention_policies WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Whether the user may build a policy on this label: their own or a system label
+    pub async fn label_visible_to_user(&self, label_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let visible = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM labels WHERE id = $1 AND (user_id = $2 OR is_system = TRUE))"
+        )
+        .bind(label_id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(visible)
+    }
+
+    /// Documents past an archive policy's retention period that it has not archived yet
+    pub async fn get_documents_due_for_archive(&self, policy_id: Uuid, limit: i64) -> Result<Vec<Document>> {
+        let query = format!(
+            r#"SELECT {} FROM documents
+               WHERE id IN (
+                   SELECT d.id FROM documents d
+                   JOIN retention_policies p ON p.id = $1
+                   WHERE {}
+                     AND NOT EXISTS (
+                         SELECT 1 FROM retention_actions a
+                         WHERE a.policy_id = p.id AND a.document_id = d.id AND a.action = 'archive'
+                     )
+                   LIMIT $2
+               )"#,
+            DOCUMENT_FIELDS, POLICY_APPLIES
+        );
+        let rows = sqlx::query(&query)
+            .bind(policy_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(map_row_to_document).collect())
+    }
+
+    /// Put documents past a delete policy's retention period in the review queue, due
+    /// when the grace period ends, and drop pending reviews of documents the policy no
+    /// longer applies to. Returns the number of documents queued.
+    pub async fn queue_retention_deletions(&self, policy_id: Uuid) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+
+        let prune = format!(
+            r#"DELETE FROM retention_reviews r
+               USING retention_policies p, documents d
+               WHERE r.policy_id = $1 AND p.id = r.policy_id AND d.id = r.document_id
+                 AND r.status = 'pending' AND NOT ({})"#,
+            POLICY_APPLIES
+        );
+        sqlx::query(&prune).bind(policy_id).execute(&mut *tx).await?;
+
+        let queue = format!(
+            r#"INSERT INTO retention_reviews (policy_id, document_id, user_id, due_at)
+               SELECT p.id, d.id, d.user_id, NOW() + make_interval(days => p.grace_days)
+               FROM documents d
+               JOIN retention_policies p ON p.id = $1
+               WHERE {}
+               ON CONFLICT (policy_id, document_id) DO NOTHING"#,
+            POLICY_APPLIES
+        );
+        let queued = sqlx::query(&queue).bind(policy_id).execute(&mut *tx).await?.rows_affected();
+
+        tx.commit().await?;
+        Ok(queued)
+    }
+
+    /// Reviews of enabled delete policies that were approved or whose grace period has
+    /// ended, for documents the policy still applies to
+    pub async fn get_due_retention_deletions(&self, limit: i64) -> Result<Vec<DueRetentionDeletion>> {
+        let query = format!(
+            r#"SELECT r.id AS review_id, p.id AS policy_id, p.name AS policy_name, d.id AS document_id, d.user_id
+               FROM retention_reviews r
+               JOIN retention_policies p ON p.id = r.policy_id
+               JOIN documents d ON d.id = r.document_id
+               WHERE p.enabled = TRUE AND p.action = 'delete'
+                 AND (r.status = 'approved' OR (r.status = 'pending' AND r.due_at <= NOW()))
+                 AND {}
+               ORDER BY r.due_at
+               LIMIT $1"#,
+            POLICY_APPLIES
+        );
+        let due = sqlx::query_as::<_, DueRetentionDeletion>(&query)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(due)
+    }
+
+    pub async fn get_retention_reviews(
+        &self,
+        user_id: Uuid,
+        status: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RetentionReview>> {
+        let reviews = sqlx::query_as::<_, RetentionReview>(
+            r#"SELECT r.id, r.policy_id, p.name AS policy_name, r.document_id, d.original_filename AS filename,
+                      r.status, r.due_at, r.created_at, r.decided_at
+               FROM retention_reviews r
+               JOIN retention_policies p ON p.id = r.policy_id
+               JOIN documents d ON d.id = r.document_id
+               WHERE r.user_id = $1 AND r.status = $2
+               ORDER BY r.due_at, d.original_filename
+               LIMIT $3 OFFSET $4"#
+        )
+        .bind(user_id)
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(reviews)
+    }
+
+    /// Approve or exempt a queued deletion. Returns false when the review does not exist.
+    pub async fn set_retention_review_status(&self, id: Uuid, user_id: Uuid, status: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE retention_reviews SET status = $3, decided_at = NOW() WHERE id = $1 AND user_id = $2"
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(status)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn record_retention_action(
+        &self,
+        policy_id: Uuid,
+        policy_name: &str,
+        document: &Document,
+        action: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO retention_actions (user_id, policy_id, policy_name, document_id, filename, file_hash, action)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)"#
+        )
+        .bind(document.user_id)
+        .bind(policy_id)
+        .bind(policy_name)
+        .bind(document.id)
+        .bind(&document.original_filename)
+        .bind(&document.file_hash)
+        .bind(action)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Archivals and deletions carried out for a user, newest first
+    pub async fn get_retention_actions(&self, user_id: Uuid, limit: i64, offset: i64) -> Result<Vec<RetentionActionRecord>> {
+        let actions = sqlx::query_as::<_, RetentionActionRecord>(
+            r#"SELECT id, policy_id, policy_name, document_id, filename, file_hash, action, performed_at
+               FROM retention_actions
+               WHERE user_id = $1
+               ORDER BY performed_at DESC
+               LIMIT $2 OFFSET $3"#
+        )
+        .bind(user_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(actions)
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::Database;
+
+impl Database {
+    /// ETag the object had when the source last ingested it, if it has
+    pub async fn get_s3_object_etag(&self, source_id: Uuid, object_key: &str) -> Result<Option<String>> {
+        let etag = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT etag FROM s3_source_objects WHERE source_id = $1 AND object_key = $2"
+        )
+        .bind(source_id)
+        .bind(object_key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(etag.flatten())
+    }
+
+    /// Object key to ETag for everything the source has ingested
+    pub async fn get_s3_object_etags(&self, source_id: Uuid) -> Result<HashMap<String, String>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT object_key, etag FROM s3_source_objects WHERE source_id = $1 AND etag IS NOT NULL"
+        )
+        .bind(source_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    pub async fn record_s3_object(
+        &self,
+        source_id: Uuid,
+        object_key: &str,
+        etag: Option<&str>,
+        document_id: Uuid,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO s3_source_objects (source_id, object_key, etag, document_id)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (source_id, object_key) DO UPDATE
+               SET etag = EXCLUDED.etag, document_id = EXCLUDED.document_id, last_seen_at = NOW()"#
+        )
+        .bind(source_id)
+        .bind(object_key)
+        .bind(etag)
+        .bind(document_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// After a poll, record the listed objects that now have a document from this source.
+    /// Keys already recorded only move to the new ETag when the sync created their document,
+    /// so an object whose new version failed to ingest is retried on the next poll.
+    pub async fn record_s3_objects_from_sync(
+        &self,
+        source_id: Uuid,
+        objects: &[(String, String)],
+        sync_started_at: DateTime<Utc>,
+    ) -> Result<u64> {
+        if objects.is_empty() {
+            return Ok(0);
+        }
+        let (keys, etags): (Vec<String>, Vec<String>) = objects.iter().cloned().unzip();
+
+        let recorded = sqlx::query(
+            r#"INSERT INTO s3_source_objects (source_id, object_key, etag, document_id)
+               SELECT DISTINCT ON (k.object_key) $1, k.object_key, k.etag, d.id
+               FROM UNNEST($2::text[], $3::text[]) AS k(object_key, etag)
+               JOIN documents d ON d.source_id = $1 AND d.source_path = k.object_key
+               WHERE d.created_at >= $4
+                  OR NOT EXISTS (
+                      SELECT 1 FROM s3_source_objects o
+                      WHERE o.source_id = $1 AND o.object_key = k.object_key
+                  )
+               ORDER BY k.object_key, d.created_at DESC
+               ON CONFLICT (source_id, object_key) DO UPDATE
+               SET etag = EXCLUDED.etag, document_id = EXCLUDED.document_id, last_seen_at = NOW()"#
+        )
+        .bind(source_id)
+        .bind(&keys)
+        .bind(&etags)
+        .bind(sync_started_at)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(recorded)
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{CreateSavedSearchRequest, SavedSearch, UpdateSavedSearchRequest};
+
+const SAVED_SEARCH_COLUMNS: &str =
+    "id, user_id, name, search, notify, last_matched_at, created_at, updated_at";
+
+impl Database {
+    pub async fn create_saved_search(
+        &self,
+        user_id: Uuid,
+        request: &CreateSavedSearchRequest,
+    ) -> Result<SavedSearch> {
+        let query = format!(
+            r#"INSERT INTO saved_searches (user_id, name, search, notify)
+               VALUES ($1, $2, $3, $4)
+               RETURNING {}"#,
+            SAVED_SEARCH_COLUMNS
+        );
+
+        let saved_search = sqlx::query_as::<_, SavedSearch>(&query)
+            .bind(user_id)
+            .bind(request.name.trim())
+            .bind(sqlx::types::Json(&request.search))
+            .bind(request.notify.unwrap_or(false))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(saved_search)
+    }
+
+    pub async fn get_saved_searches(&self, user_id: Uuid) -> Result<Vec<SavedSearch>> {
+        let query = format!(
+            "SELECT {} FROM saved_searches WHERE user_id = $1 ORDER BY name",
+            SAVED_SEARCH_COLUMNS
+        );
+        let saved_searches = sqlx::query_as::<_, SavedSearch>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(saved_searches)
+    }
+
+    /// Saved searches of every user that asked to be notified of new matches
+    pub async fn get_notifying_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        let query = format!("SELECT {} FROM saved_searches WHERE notify", SAVED_SEARCH_COLUMNS);
+        let saved_searches = sqlx::query_as::<_, SavedSearch>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(saved_searches)
+    }
+
+    pub async fn get_saved_search(&self, id: Uuid, user_id: Uuid) -> Result<Option<SavedSearch>> {
+        let query = format!(
+            "SELECT {} FROM saved_searches WHERE id = $1 AND user_id = $2",
+            SAVED_SEARCH_COLUMNS
+        );
+        let saved_search = sqlx::query_as::<_, SavedSearch>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(saved_search)
+    }
+
+    /// Whether the user already has another saved search with this name
+    pub async fn saved_search_name_taken(&self, user_id: Uuid, name: &str, except: Option<Uuid>) -> Result<bool> {
+        let taken: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS (
+                   SELECT 1 FROM saved_searches
+                   WHERE user_id = $1 AND name = $2 AND ($3::uuid IS NULL OR id <> $3)
+               )"#,
+        )
+        .bind(user_id)
+        .bind(name.trim())
+        .bind(except)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(taken)
+    }
+
+    pub async fn update_saved_search(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        request: &UpdateSavedSearchRequest,
+    ) -> Result<Option<SavedSearch>> {
+        let query = format!(
+            r#"UPDATE saved_searches
+               SET name = COALESCE($3, name),
+                   search = COALESCE($4, search),
+                   notify = COALESCE($5, notify),
+                   updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            SAVED_SEARCH_COLUMNS
+        );
+
+        let saved_search = sqlx::query_as::<_, SavedSearch>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(request.name.as_deref().map(str::trim))
+            .bind(request.search.as_ref().map(sqlx::types::Json))
+            .bind(request.notify)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(saved_search)
+    }
+
+    pub async fn delete_saved_search(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM saved_searches WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn touch_saved_search_match(&self, id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE saved_searches SET last_matched_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{PendingScanBridgePairing, ScanBridge};
+
+const SCAN_BRIDGE_FIELDS: &str = "id, user_id, name, token_prefix, enabled, platform, version, pages_received, \
+     documents_received, last_used_at, last_address, created_at, updated_at";
+const PAIRING_FIELDS: &str = "user_code, name, platform, version, address, approved_at, expires_at";
+
+impl Database {
+    pub async fn get_scan_bridges(&self, user_id: Uuid) -> Result<Vec<ScanBridge>> {
+        let query = format!(
+            "SELECT {} FROM scan_bridges WHERE user_id = $1 ORDER BY created_at DESC",
+            SCAN_BRIDGE_FIELDS
+        );
+        let bridges = sqlx::query_as::<_, ScanBridge>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(bridges)
+    }
+
+    pub async fn count_scan_bridges(&self, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM scan_bridges WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// The enabled bridge a token belongs to
+    pub async fn get_scan_bridge_by_token_hash(&self, token_hash: &str) -> Result<Option<ScanBridge>> {
+        let query = format!(
+            "SELECT {} FROM scan_bridges WHERE token_hash = $1 AND enabled",
+            SCAN_BRIDGE_FIELDS
+        );
+        let bridge = sqlx::query_as::<_, ScanBridge>(&query)
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(bridge)
+    }
+
+    /// Returns None when the user has no such bridge
+    pub async fn update_scan_bridge(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        name: Option<&str>,
+        enabled: Option<bool>,
+    ) -> Result<Option<ScanBridge>> {
+        let query = format!(
+            r#"UPDATE scan_bridges
+               SET name = COALESCE($3, name), enabled = COALESCE($4, enabled), updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            SCAN_BRIDGE_FIELDS
+        );
+        let bridge = sqlx::query_as::<_, ScanBridge>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(name.map(str::trim))
+            .bind(enabled)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(bridge)
+    }
+
+    /// Note that a bridge was used from `address`
+    pub async fn touch_scan_bridge(&self, id: Uuid, address: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE scan_bridges SET last_used_at = NOW(), last_address = COALESCE($2, last_address) WHERE id = $1")
+            .bind(id)
+            .bind(address)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn count_scan_bridge_document(&self, id: Uuid, pages: i64) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE scan_bridges
+               SET documents_received = documents_received + 1, pages_received = pages_received + $2
+               WHERE id = $1"#,
+        )
+        .bind(id)
+        .bind(pages)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns false when the user has no such bridge
+    pub async fn delete_scan_bridge(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM scan_bridges WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Start a pairing; expired pairings are cleared on the way
+    pub async fn create_scan_bridge_pairing(
+        &self,
+        device_code_hash: &str,
+        user_code: &str,
+        name: &str,
+        platform: Option<&str>,
+        version: Option<&str>,
+        address: Option<&str>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM scan_bridge_pairings WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query(
+            r#"INSERT INTO scan_bridge_pairings (device_code_hash, user_code, name, platform, version, address, expires_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        )
+        .bind(device_code_hash)
+        .bind(user_code)
+        .bind(name)
+        .bind(platform)
+        .bind(version)
+        .bind(address)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// An unexpired pairing, approved or not
+    pub async fn get_scan_bridge_pairing(&self, user_code: &str) -> Result<Option<PendingScanBridgePairing>> {
+        let query = format!(
+            "SELECT {} FROM scan_bridge_pairings WHERE user_code = $1 AND expires_at > NOW()",
+            PAIRING_FIELDS
+        );
+        let pairing = sqlx::query_as::<_, PendingScanBridgePairing>(&query)
+            .bind(user_code)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(pairing)
+    }
+
+    /// Approve an unexpired pairing nobody approved yet, optionally renaming the bridge.
+    /// Returns None when there is no such pairing.
+    pub async fn approve_scan_bridge_pairing(
+        &self,
+        user_code: &str,
+        user_id: Uuid,
+        name: Option<&str>,
+    ) -> Result<Option<PendingScanBridgePairing>> {
+        let query = format!(
+            r#"UPDATE scan_bridge_pairings
+               SET approved_by = $2, approved_at = NOW(), name = COALESCE($3, name)
+               WHERE user_code = $1 AND approved_by IS NULL AND expires_at > NOW()
+               RETURNING {}"#,
+            PAIRING_FIELDS
+        );
+        let pairing = sqlx::query_as::<_, PendingScanBridgePairing>(&query)
+            .bind(user_code)
+            .bind(user_id)
+            .bind(name.map(str::trim))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(pairing)
+    }
+
+    /// Whether an unexpired pairing with this device code exists and was approved.
+    /// None when there is no such pairing.
+    pub async fn scan_bridge_pairing_approved(&self, device_code_hash: &str) -> Result<Option<bool>> {
+        let approved = sqlx::query_scalar(
+            "SELECT approved_by IS NOT NULL FROM scan_bridge_pairings WHERE device_code_hash = $1 AND expires_at > NOW()",
+        )
+        .bind(device_code_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(approved)
+    }
+
+    /// Turn an approved pairing into a bridge owned by the approving user, with the
+    /// given token. The pairing is used up, so a device code yields one token. Returns
+    /// None when the pairing is gone or not approved.
+    pub async fn complete_scan_bridge_pairing(
+        &self,
+        device_code_hash: &str,
+        token_hash: &str,
+        token_prefix: &str,
+    ) -> Result<Option<ScanBridge>> {
+        let query = format!(
+            r#"WITH pairing AS (
+                   DELETE FROM scan_bridge_pairings
+                   WHERE device_code_hash = $1 AND approved_by IS NOT NULL AND expires_at > NOW()
+                   RETURNING approved_by, name, platform, version, address
+               )
+               INSERT INTO scan_bridges (user_id, name, token_hash, token_prefix, platform, version, last_address)
+               SELECT approved_by, name, $2, $3, platform, version, address FROM pairing
+               RETURNING {}"#,
+            SCAN_BRIDGE_FIELDS
+        );
+        let bridge = sqlx::query_as::<_, ScanBridge>(&query)
+            .bind(device_code_hash)
+            .bind(token_hash)
+            .bind(token_prefix)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(bridge)
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use sqlx::Row;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::ScanDevice;
+
+const SCAN_DEVICE_FIELDS: &str =
+    "id, user_id, name, username, enabled, documents_received, last_used_at, last_address, created_at, updated_at";
+
+impl Database {
+    pub async fn create_scan_device(&self, user_id: Uuid, name: &str, username: &str, password_hash: &str) -> Result<ScanDevice> {
+        let query = format!(
+            r#"INSERT INTO scan_devices (user_id, name, username, password_hash)
+               VALUES ($1, $2, $3, $4)
+               RETURNING {}"#,
+            SCAN_DEVICE_FIELDS
+        );
+        let device = sqlx::query_as::<_, ScanDevice>(&query)
+            .bind(user_id)
+            .bind(name.trim())
+            .bind(username)
+            .bind(password_hash)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(device)
+    }
+
+    pub async fn get_scan_devices(&self, user_id: Uuid) -> Result<Vec<ScanDevice>> {
+        let query = format!(
+            "SELECT {} FROM scan_devices WHERE user_id = $1 ORDER BY created_at DESC",
+            SCAN_DEVICE_FIELDS
+        );
+        let devices = sqlx::query_as::<_, ScanDevice>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(devices)
+    }
+
+    /// The device signing in as `username`, with its password hash
+    pub async fn get_scan_device_login(&self, username: &str) -> Result<Option<(ScanDevice, String)>> {
+        let query = format!(
+            "SELECT {}, password_hash FROM scan_devices WHERE LOWER(username) = LOWER($1)",
+            SCAN_DEVICE_FIELDS
+        );
+        let row = sqlx::query(&query).bind(username).fetch_optional(&self.pool).await?;
+        row.map(|row| {
+            let password_hash: String = row.try_get("password_hash")?;
+            Ok((sqlx::FromRow::from_row(&row)?, password_hash))
+        })
+        .transpose()
+    }
+
+    /// Returns None when the user has no such device
+    pub async fn update_scan_device(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        name: Option<&str>,
+        enabled: Option<bool>,
+    ) -> Result<Option<ScanDevice>> {
+        let query = format!(
+            r#"UPDATE scan_devices
+               SET name = COALESCE($3, name), enabled = COALESCE($4, enabled), updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            SCAN_DEVICE_FIELDS
+        );
+        let device = sqlx::query_as::<_, ScanDevice>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(name.map(str::trim))
+            .bind(enabled)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(device)
+    }
+
+    /// Returns None when the user has no such device
+    pub async fn set_scan_device_password(&self, id: Uuid, user_id: Uuid, password_hash: &str) -> Result<Option<ScanDevice>> {
+        let query = format!(
+            r#"UPDATE scan_devices SET password_hash = $3, updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            SCAN_DEVICE_FIELDS
+        );
+        let device = sqlx::query_as::<_, ScanDevice>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(password_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(device)
+    }
+
+    /// Note that a device signed in from `address`
+    pub async fn touch_scan_device(&self, id: Uuid, address: &str) -> Result<()> {
+        sqlx::query("UPDATE scan_devices SET last_used_at = NOW(), last_address = $2 WHERE id = $1")
+            .bind(id)
+            .bind(address)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn count_scan_device_document(&self, id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE scan_devices SET documents_received = documents_received + 1 WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns false when the user has no such device
+    pub async fn delete_scan_device(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM scan_devices WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
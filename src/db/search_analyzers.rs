@@ -0,0 +1,251 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{SearchAnalyzer, SearchIndexRebuild, SearchSynonym};
+
+/// Keeps the documents indexed in language `$1`, or every document when it is NULL.
+/// Documents are indexed in their owner's language, as in readur_document_search_vector().
+const LANGUAGE_FILTER: &str = r#"
+    ($1::text IS NULL OR readur_search_config((
+        SELECT COALESCE(s.primary_language, s.ocr_language) FROM settings s WHERE s.user_id = documents.user_id
+    )) = ('readur_' || $1)::regconfig)
+"#;
+
+impl Database {
+    /// Analyzers admins have changed; other languages use the built-in one
+    pub async fn list_search_analyzers(&self) -> Result<Vec<SearchAnalyzer>> {
+        let analyzers = sqlx::query_as::<_, SearchAnalyzer>(
+            "SELECT language, fold_accents, dictionaries, updated_by, updated_at FROM search_analyzers",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(analyzers)
+    }
+
+    /// Those of the dictionaries that are not installed in the database
+    pub async fn missing_search_dictionaries(&self, dictionaries: &[String]) -> Result<Vec<String>> {
+        let installed = sqlx::query_scalar::<_, String>("SELECT dictname::text FROM pg_ts_dict WHERE dictname::text = ANY($1)")
+            .bind(dictionaries)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(dictionaries.iter().filter(|name| !installed.contains(name)).cloned().collect())
+    }
+
+    /// Points the language's text search configuration at the dictionaries and keeps
+    /// the choice. Documents already indexed keep their lexemes until they are rebuilt.
+    pub async fn apply_search_analyzer(
+        &self,
+        language: &str,
+        fold_accents: bool,
+        dictionaries: &[String],
+        updated_by: Uuid,
+    ) -> Result<SearchAnalyzer> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT readur_configure_search_analyzer($1, $2, $3)")
+            .bind(language)
+            .bind(fold_accents)
+            .bind(dictionaries)
+            .execute(&mut *tx)
+            .await?;
+        let analyzer = sqlx::query_as::<_, SearchAnalyzer>(
+            r#"
+            INSERT INTO search_analyzers (language, fold_accents, dictionaries, updated_by, updated_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            ON CONFLICT (language) DO UPDATE
+            SET fold_accents = EXCLUDED.fold_accents, dictionaries = EXCLUDED.dictionaries,
+                updated_by = EXCLUDED.updated_by, updated_at = NOW()
+            RETURNING language, fold_accents, dictionaries, updated_by, updated_at
+            "#,
+        )
+        .bind(language)
+        .bind(fold_accents)
+        .bind(dictionaries)
+        .bind(updated_by)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(analyzer)
+    }
+
+    pub async fn list_search_synonyms(&self, language: Option<&str>) -> Result<Vec<SearchSynonym>> {
+        let synonyms = sqlx::query_as::<_, SearchSynonym>(
+            "SELECT * FROM search_synonyms WHERE ($1::text IS NULL OR language = $1) ORDER BY language, term",
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(synonyms)
+    }
+
+    /// Sets the synonyms of a term, replacing those it had
+    pub async fn upsert_search_synonym(
+        &self,
+        language: &str,
+        term: &str,
+        synonyms: &[String],
+        created_by: Uuid,
+    ) -> Result<SearchSynonym> {
+        let synonym = sqlx::query_as::<_, SearchSynonym>(
+            r#"
+            INSERT INTO search_synonyms (language, term, synonyms, created_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (language, term) DO UPDATE
+            SET synonyms = EXCLUDED.synonyms, created_by = EXCLUDED.created_by, created_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(language)
+        .bind(term)
+        .bind(synonyms)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(synonym)
+    }
+
+    /// Returns false if there was no such entry
+    pub async fn delete_search_synonym(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM search_synonyms WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records a new rebuild of the documents indexed in `language`, or of every
+    /// document. None while another rebuild is running.
+    pub async fn start_search_index_rebuild(
+        &self,
+        language: Option<&str>,
+        started_by: Uuid,
+    ) -> Result<Option<SearchIndexRebuild>> {
+        let query = format!(
+            r#"
+            INSERT INTO search_index_rebuilds (language, started_by, total_documents)
+            VALUES ($1, $2, (SELECT COUNT(*) FROM documents WHERE {}))
+            ON CONFLICT DO NOTHING
+            RETURNING *
+            "#,
+            LANGUAGE_FILTER
+        );
+        let rebuild = sqlx::query_as::<_, SearchIndexRebuild>(&query)
+            .bind(language)
+            .bind(started_by)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(rebuild)
+    }
+
+    pub async fn get_search_index_rebuild(&self, id: Uuid) -> Result<Option<SearchIndexRebuild>> {
+        let rebuild = sqlx::query_as::<_, SearchIndexRebuild>("SELECT * FROM search_index_rebuilds WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(rebuild)
+    }
+
+    /// The most recent rebuilds, newest first
+    pub async fn list_search_index_rebuilds(&self, limit: i64) -> Result<Vec<SearchIndexRebuild>> {
+        let rebuilds = sqlx::query_as::<_, SearchIndexRebuild>(
+            "SELECT * FROM search_index_rebuilds ORDER BY started_at DESC LIMIT $1",
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rebuilds)
+    }
+
+    /// The rebuild left running by the last shutdown, if any
+    pub async fn get_running_search_index_rebuild(&self) -> Result<Option<SearchIndexRebuild>> {
+        let rebuild =
+            sqlx::query_as::<_, SearchIndexRebuild>("SELECT * FROM search_index_rebuilds WHERE status = 'running'")
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(rebuild)
+    }
+
+    /// Recomputes the search vectors of the rebuild's next `batch_size` documents after
+    /// `after`, in id order, and records the progress. Returns the last document
+    /// rebuilt, or None when the rebuild has gone through every document.
+    pub async fn rebuild_search_index_batch(
+        &self,
+        rebuild: &SearchIndexRebuild,
+        after: Option<Uuid>,
+        batch_size: i64,
+    ) -> Result<Option<Uuid>> {
+        let query = format!(
+            r#"
+            UPDATE documents
+            SET search_vector = readur_document_search_vector(user_id, content, ocr_text)
+            WHERE id IN (
+                SELECT id FROM documents
+                WHERE {}
+                  AND ($2::uuid IS NULL OR id > $2)
+                ORDER BY id
+                LIMIT $3
+            )
+            RETURNING id
+            "#,
+            LANGUAGE_FILTER
+        );
+
+        let mut tx = self.pool.begin().await?;
+        let ids = sqlx::query_scalar::<_, Uuid>(&query)
+            .bind(rebuild.language.as_deref())
+            .bind(after)
+            .bind(batch_size)
+            .fetch_all(&mut *tx)
+            .await?;
+        let last_id = ids.iter().max().copied();
+        if let Some(last_id) = last_id {
+            sqlx::query(
+                r#"
+                UPDATE search_index_rebuilds
+                SET processed_documents = processed_documents + $2, last_document_id = $3,
+                    total_documents = GREATEST(total_documents, processed_documents + $2)
+                WHERE id = $1
+                "#,
+            )
+            .bind(rebuild.id)
+            .bind(ids.len() as i64)
+            .bind(last_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(last_id)
+    }
+
+    /// Recomputes the search vectors of the annotations, comments and custom fields of
+    /// the documents indexed in `language`, or of every document
+    pub async fn rebuild_field_search_vectors(&self, language: Option<&str>) -> Result<u64> {
+        let query = format!(
+            r#"
+            UPDATE document_field_search
+            SET search_vector = readur_document_field_search_vector(document_field_search.document_id, document_field_search.text)
+            FROM documents
+            WHERE documents.id = document_field_search.document_id AND {}
+            "#,
+            LANGUAGE_FILTER
+        );
+        let result = sqlx::query(&query).bind(language).execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn finish_search_index_rebuild(&self, id: Uuid, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE search_index_rebuilds
+            SET status = CASE WHEN $2::text IS NULL THEN 'completed' ELSE 'failed' END,
+                error = $2, completed_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::UserSession;
+
+const SESSION_FIELDS: &str =
+    "id, user_id, method, ip_address, user_agent, created_at, last_seen_at, expires_at";
+const ACTIVE_CONDITION: &str = "revoked_at IS NULL AND expires_at > NOW()";
+
+impl Database {
+    /// Starts a session, clearing out the user's sessions that ended a month ago
+    pub async fn create_user_session(
+        &self,
+        user_id: Uuid,
+        method: &str,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<UserSession> {
+        sqlx::query(
+            r#"DELETE FROM user_sessions
+               WHERE user_id = $1 AND COALESCE(revoked_at, expires_at) < NOW() - INTERVAL '30 days'"#
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        let query = format!(
+            r#"INSERT INTO user_sessions (user_id, method, ip_address, user_agent, expires_at)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING {}"#,
+            SESSION_FIELDS
+        );
+        let session = sqlx::query_as::<_, UserSession>(&query)
+            .bind(user_id)
+            .bind(method)
+            .bind(ip_address)
+            .bind(user_agent)
+            .bind(expires_at)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(session)
+    }
+
+    /// A session that has been neither revoked nor expired
+    pub async fn get_active_session(&self, id: Uuid) -> Result<Option<UserSession>> {
+        let query = format!(
+            "SELECT {} FROM user_sessions WHERE id = $1 AND {}",
+            SESSION_FIELDS, ACTIVE_CONDITION
+        );
+        let session = sqlx::query_as::<_, UserSession>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(session)
+    }
+
+    /// Active sessions of a user, most recently used first
+    pub async fn get_user_sessions(&self, user_id: Uuid) -> Result<Vec<UserSession>> {
+        let query = format!(
+            "SELECT {} FROM user_sessions WHERE user_id = $1 AND {} ORDER BY last_seen_at DESC",
+            SESSION_FIELDS, ACTIVE_CONDITION
+        );
+        let sessions = sqlx::query_as::<_, UserSession>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(sessions)
+    }
+
+    /// Records activity on a session, at most once a minute to spare writes
+    pub async fn touch_user_session(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE user_sessions SET last_seen_at = NOW()
+               WHERE id = $1 AND last_seen_at < NOW() - INTERVAL '1 minute'"#
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revokes one of a user's active sessions; false when there is no such session
+    pub async fn revoke_user_session(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let query = format!(
+            "UPDATE user_sessions SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND {}",
+            ACTIVE_CONDITION
+        );
+        let result = sqlx::query(&query)
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revokes every active session of a user except `keep`, returning how many ended
+    pub async fn revoke_user_sessions(&self, user_id: Uuid, keep: Option<Uuid>) -> Result<u64> {
+        let query = format!(
+            "UPDATE user_sessions SET revoked_at = NOW() WHERE user_id = $1 AND ($2::uuid IS NULL OR id <> $2) AND {}",
+            ACTIVE_CONDITION
+        );
+        let result = sqlx::query(&query)
+            .bind(user_id)
+            .bind(keep)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::ShareLink;
+
+const SHARE_LINK_SELECT: &str = r#"
+    SELECT l.id, l.document_id, d.original_filename AS document_filename, l.created_by,
+           u.username AS created_by_username, l.password_hash, l.password_hash IS NOT NULL AS has_password,
+           l.expires_at, l.max_downloads, l.download_count, l.watermark, l.watermark_text,
+           l.revoked_at, l.last_accessed_at, l.created_at
+    FROM document_share_links l
+    JOIN documents d ON d.id = l.document_id
+    LEFT JOIN users u ON u.id = l.created_by
+"#;
+
+const ACTIVE_CONDITION: &str = "l.revoked_at IS NULL \
+    AND (l.expires_at IS NULL OR l.expires_at > NOW()) \
+    AND (l.max_downloads IS NULL OR l.download_count < l.max_downloads)";
+
+/// A share link to create; hashes are computed by the caller
+pub struct NewShareLink<'a> {
+    pub document_id: Uuid,
+    pub created_by: Uuid,
+    pub token_hash: &'a str,
+    pub password_hash: Option<&'a str>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_downloads: Option<i32>,
+    pub watermark: bool,
+    pub watermark_text: Option<&'a str>,
+}
+
+impl Database {
+    pub async fn create_share_link(&self, link: &NewShareLink<'_>) -> Result<ShareLink> {
+        let id: Uuid = sqlx::query_scalar(
+            r#"INSERT INTO document_share_links
+                   (document_id, created_by, token_hash, password_hash, expires_at, max_downloads, watermark, watermark_text)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+               RETURNING id"#
+        )
+        .bind(link.document_id)
+        .bind(link.created_by)
+        .bind(link.token_hash)
+        .bind(link.password_hash)
+        .bind(link.expires_at)
+        .bind(link.max_downloads)
+        .bind(link.watermark)
+        .bind(link.watermark_text)
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.get_share_link(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Share link {} disappeared after creation", id))
+    }
+
+    pub async fn get_share_link(&self, id: Uuid) -> Result<Option<ShareLink>> {
+        let query = format!("{} WHERE l.id = $1", SHARE_LINK_SELECT);
+        let link = sqlx::query_as::<_, ShareLink>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(link)
+    }
+
+    /// The link a token belongs to, whether or not it is still active
+    pub async fn get_share_link_by_token_hash(&self, token_hash: &str) -> Result<Option<ShareLink>> {
+        let query = format!("{} WHERE l.token_hash = $1", SHARE_LINK_SELECT);
+        let link = sqlx::query_as::<_, ShareLink>(&query)
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(link)
+    }
+
+    pub async fn get_document_share_links(&self, document_id: Uuid) -> Result<Vec<ShareLink>> {
+        let query = format!("{} WHERE l.document_id = $1 ORDER BY l.created_at DESC", SHARE_LINK_SELECT);
+        let links = sqlx::query_as::<_, ShareLink>(&query)
+            .bind(document_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(links)
+    }
+
+    /// Links of every user, newest first; only those still usable unless `include_inactive`
+    pub async fn get_share_links(&self, include_inactive: bool, limit: i64, offset: i64) -> Result<Vec<ShareLink>> {
+        let mut query = QueryBuilder::<Postgres>::new(SHARE_LINK_SELECT);
+        if !include_inactive {
+            query.push(" WHERE ").push(ACTIVE_CONDITION);
+        }
+        query
+            .push(" ORDER BY l.created_at DESC LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let links = query.build_query_as::<ShareLink>().fetch_all(&self.pool).await?;
+        Ok(links)
+    }
+
+    /// Counts a download against the link. Returns false when the link was revoked,
+    /// expired or used up, including by a concurrent download of the last allowed copy.
+    pub async fn claim_share_link_download(&self, id: Uuid) -> Result<bool> {
+        let query = format!(
+            r#"UPDATE document_share_links l
+               SET download_count = l.download_count + 1, last_accessed_at = NOW()
+               WHERE l.id = $1 AND {}"#,
+            ACTIVE_CONDITION
+        );
+        let result = sqlx::query(&query).bind(id).execute(&self.pool).await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns false when the link does not exist or was already revoked
+    pub async fn revoke_share_link(&self, id: Uuid, revoked_by: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE document_share_links SET revoked_at = NOW(), revoked_by = $2 WHERE id = $1 AND revoked_at IS NULL"
+        )
+        .bind(id)
+        .bind(revoked_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
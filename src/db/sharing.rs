@@ -0,0 +1,357 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::documents::embargoed_condition;
+use super::Database;
+use crate::models::{Share, SharePermission, SharedDocument, UserGroup, UserGroupMember};
+
+const USER_GROUP_SELECT: &str = r#"
+    SELECT g.id, g.name, g.description, g.created_by, g.created_at, g.updated_at,
+           (SELECT COUNT(*) FROM user_group_members m WHERE m.group_id = g.id) AS member_count
+    FROM user_groups g
+"#;
+
+/// What can be shared: the share table and the column naming the shared row
+#[derive(Debug, Clone, Copy)]
+enum ShareTarget {
+    Document,
+    Label,
+    Collection,
+}
+
+impl ShareTarget {
+    fn table(self) -> &'static str {
+        match self {
+            ShareTarget::Document => "document_shares",
+            ShareTarget::Label => "label_shares",
+            ShareTarget::Collection => "collection_shares",
+        }
+    }
+
+    fn column(self) -> &'static str {
+        match self {
+            ShareTarget::Document => "document_id",
+            ShareTarget::Label => "label_id",
+            ShareTarget::Collection => "collection_id",
+        }
+    }
+}
+
+impl Database {
+    pub async fn create_user_group(&self, name: &str, description: Option<&str>, created_by: Uuid) -> Result<UserGroup> {
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO user_groups (name, description, created_by) VALUES ($1, $2, $3) RETURNING id"
+        )
+        .bind(name.trim())
+        .bind(description)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.get_user_group(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Group {} disappeared after creation", id))
+    }
+
+    pub async fn get_user_groups(&self) -> Result<Vec<UserGroup>> {
+        let query = format!("{} ORDER BY LOWER(g.name)", USER_GROUP_SELECT);
+        let groups = sqlx::query_as::<_, UserGroup>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(groups)
+    }
+
+    /// Groups the user is a member of
+    pub async fn get_groups_for_user(&self, user_id: Uuid) -> Result<Vec<UserGroup>> {
+        let query = format!(
+            "{} WHERE g.id IN (SELECT group_id FROM user_group_members WHERE user_id = $1) ORDER BY LOWER(g.name)",
+            USER_GROUP_SELECT
+        );
+        let groups = sqlx::query_as::<_, UserGroup>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(groups)
+    }
+
+    pub async fn get_user_group(&self, id: Uuid) -> Result<Option<UserGroup>> {
+        let query = format!("{} WHERE g.id = $1", USER_GROUP_SELECT);
+        let group = sqlx::query_as::<_, UserGroup>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(group)
+    }
+
+    pub async fn get_user_group_by_name(&self, name: &str) -> Result<Option<UserGroup>> {
+        let query = format!("{} WHERE g.name = $1", USER_GROUP_SELECT);
+        let group = sqlx::query_as::<_, UserGroup>(&query)
+            .bind(name.trim())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(group)
+    }
+
+    /// The ID of the group with this name, creating it when there is none. Used for
+    /// groups provisioned from an identity provider, so it has no creator.
+    pub async fn ensure_user_group(&self, name: &str) -> Result<Uuid> {
+        let id: Uuid = sqlx::query_scalar(
+            r#"INSERT INTO user_groups (name) VALUES ($1)
+               ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name
+               RETURNING id"#
+        )
+        .bind(name.trim())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Returns false when the group does not exist
+    pub async fn update_user_group(&self, id: Uuid, name: &str, description: Option<&str>) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE user_groups SET name = $2, description = $3, updated_at = NOW() WHERE id = $1"
+        )
+        .bind(id)
+        .bind(name.trim())
+        .bind(description)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletes a group along with everything shared with it
+    pub async fn delete_user_group(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM user_groups WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_user_group_members(&self, group_id: Uuid) -> Result<Vec<UserGroupMember>> {
+        let members = sqlx::query_as::<_, UserGroupMember>(
+            r#"SELECT m.user_id, u.username, m.added_at
+               FROM user_group_members m
+               JOIN users u ON u.id = m.user_id
+               WHERE m.group_id = $1
+               ORDER BY LOWER(u.username)"#
+        )
+        .bind(group_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(members)
+    }
+
+    /// Returns false when the user already was a member
+    pub async fn add_user_group_member(&self, group_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO user_group_members (group_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+        )
+        .bind(group_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn remove_user_group_member(&self, group_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM user_group_members WHERE group_id = $1 AND user_id = $2")
+            .bind(group_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Owner of a label; None for system labels and labels that do not exist
+    pub async fn get_label_owner(&self, label_id: Uuid) -> Result<Option<Uuid>> {
+        let owner: Option<Option<Uuid>> = sqlx::query_scalar("SELECT user_id FROM labels WHERE id = $1")
+            .bind(label_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(owner.flatten())
+    }
+
+    /// Whether any document is shared with the user, directly, through a group, a label
+    /// or a collection
+    pub async fn has_shared_documents(&self, user_id: Uuid) -> Result<bool> {
+        let shared = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM shared_document_access WHERE user_id = $1)"
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(shared)
+    }
+
+    /// Documents of other users shared with the user, newest first, with the highest
+    /// permission granted
+    pub async fn get_documents_shared_with_user(&self, user_id: Uuid, limit: i64, offset: i64) -> Result<Vec<SharedDocument>> {
+        let query = format!(
+            r#"SELECT d.id, d.original_filename, d.mime_type, d.file_size, d.user_id AS owner_id,
+                      u.username AS owner_username, a.permission, d.created_at
+               FROM (
+                   SELECT document_id, MAX(permission) AS permission
+                   FROM shared_document_access
+                   WHERE user_id = $1
+                   GROUP BY document_id
+               ) a
+               JOIN documents d ON d.id = a.document_id
+               JOIN users u ON u.id = d.user_id
+               WHERE d.user_id <> $1 AND NOT {}
+               ORDER BY d.created_at DESC
+               LIMIT $2 OFFSET $3"#,
+            embargoed_condition("d.id")
+        );
+        let documents = sqlx::query_as::<_, SharedDocument>(&query)
+        .bind(user_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(documents)
+    }
+
+    pub async fn share_document(
+        &self,
+        document_id: Uuid,
+        user_id: Option<Uuid>,
+        group_id: Option<Uuid>,
+        permission: SharePermission,
+        granted_by: Uuid,
+    ) -> Result<Share> {
+        self.create_share(ShareTarget::Document, document_id, user_id, group_id, permission, granted_by).await
+    }
+
+    pub async fn share_label(
+        &self,
+        label_id: Uuid,
+        user_id: Option<Uuid>,
+        group_id: Option<Uuid>,
+        permission: SharePermission,
+        granted_by: Uuid,
+    ) -> Result<Share> {
+        self.create_share(ShareTarget::Label, label_id, user_id, group_id, permission, granted_by).await
+    }
+
+    pub async fn share_collection(
+        &self,
+        collection_id: Uuid,
+        user_id: Option<Uuid>,
+        group_id: Option<Uuid>,
+        permission: SharePermission,
+        granted_by: Uuid,
+    ) -> Result<Share> {
+        self.create_share(ShareTarget::Collection, collection_id, user_id, group_id, permission, granted_by).await
+    }
+
+    pub async fn get_document_shares(&self, document_id: Uuid) -> Result<Vec<Share>> {
+        self.get_shares(ShareTarget::Document, document_id).await
+    }
+
+    pub async fn get_label_shares(&self, label_id: Uuid) -> Result<Vec<Share>> {
+        self.get_shares(ShareTarget::Label, label_id).await
+    }
+
+    pub async fn get_collection_shares(&self, collection_id: Uuid) -> Result<Vec<Share>> {
+        self.get_shares(ShareTarget::Collection, collection_id).await
+    }
+
+    pub async fn delete_document_share(&self, share_id: Uuid, document_id: Uuid) -> Result<bool> {
+        self.delete_share(ShareTarget::Document, share_id, document_id).await
+    }
+
+    pub async fn delete_label_share(&self, share_id: Uuid, label_id: Uuid) -> Result<bool> {
+        self.delete_share(ShareTarget::Label, share_id, label_id).await
+    }
+
+    pub async fn delete_collection_share(&self, share_id: Uuid, collection_id: Uuid) -> Result<bool> {
+        self.delete_share(ShareTarget::Collection, share_id, collection_id).await
+    }
+
+    /// Share with exactly one of `user_id` and `group_id`, replacing the permission of an
+    /// existing share with the same grantee
+    async fn create_share(
+        &self,
+        target: ShareTarget,
+        resource_id: Uuid,
+        user_id: Option<Uuid>,
+        group_id: Option<Uuid>,
+        permission: SharePermission,
+        granted_by: Uuid,
+    ) -> Result<Share> {
+        let grantee = if user_id.is_some() { "user_id" } else { "group_id" };
+        let query = format!(
+            r#"INSERT INTO {table} ({column}, user_id, group_id, permission, granted_by)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT ({column}, {grantee}) WHERE {grantee} IS NOT NULL
+               DO UPDATE SET permission = EXCLUDED.permission, granted_by = EXCLUDED.granted_by
+               RETURNING id"#,
+            table = target.table(),
+            column = target.column(),
+            grantee = grantee,
+        );
+        let id: Uuid = sqlx::query_scalar(&query)
+            .bind(resource_id)
+            .bind(user_id)
+            .bind(group_id)
+            .bind(permission)
+            .bind(granted_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        self.get_shares(target, resource_id)
+            .await?
+            .into_iter()
+            .find(|share| share.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Share {} disappeared after creation", id))
+    }
+
+    async fn get_shares(&self, target: ShareTarget, resource_id: Uuid) -> Result<Vec<Share>> {
+        let query = format!(
+            r#"SELECT s.id, s.{column} AS resource_id, s.user_id, u.username, s.group_id, g.name AS group_name,
+                      s.permission, s.granted_by, s.created_at
+               FROM {table} s
+               LEFT JOIN users u ON u.id = s.user_id
+               LEFT JOIN user_groups g ON g.id = s.group_id
+               WHERE s.{column} = $1
+               ORDER BY s.created_at"#,
+            table = target.table(),
+            column = target.column(),
+        );
+        let shares = sqlx::query_as::<_, Share>(&query)
+            .bind(resource_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(shares)
+    }
+
+    async fn delete_share(&self, target: ShareTarget, share_id: Uuid, resource_id: Uuid) -> Result<bool> {
+        let query = format!(
+            "DELETE FROM {} WHERE id = $1 AND {} = $2",
+            target.table(),
+            target.column()
+        );
+        let result = sqlx::query(&query)
+            .bind(share_id)
+            .bind(resource_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::documents::{map_row_to_document, DOCUMENT_FIELDS};
+use super::Database;
+use crate::models::Document;
+
+/// Documents that can be rendered into pages for perceptual hashing
+const HASHABLE_DOCUMENTS: &str = "(mime_type = 'application/pdf' OR mime_type LIKE 'image/%')";
+
+impl Database {
+    /// Replace the page hashes of a document; `hashes[0]` is page 1
+    pub async fn replace_document_page_hashes(&self, document_id: Uuid, hashes: &[i64]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM document_page_hashes WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"INSERT INTO document_page_hashes (document_id, page_number, hash)
+               SELECT $1, page_number::int, hash FROM UNNEST($2::bigint[]) WITH ORDINALITY AS pages(hash, page_number)"#
+        )
+        .bind(document_id)
+        .bind(hashes)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Page hashes of every hashed document of a user, oldest document first
+    pub async fn get_user_page_hashes(&self, user_id: Uuid) -> Result<Vec<(Uuid, Vec<i64>)>> {
+        let rows = sqlx::query_as::<_, (Uuid, Vec<i64>)>(
+            r#"SELECT h.document_id, array_agg(h.hash ORDER BY h.page_number)
+               FROM document_page_hashes h
+               JOIN documents d ON d.id = h.document_id
+               WHERE d.user_id = $1
+               GROUP BY h.document_id, d.created_at
+               ORDER BY d.created_at, h.document_id"#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// A user's renderable documents without page hashes, in ID order after `after`
+    pub async fn get_unhashed_documents(&self, user_id: Uuid, after: Option<Uuid>, limit: i64) -> Result<Vec<Document>> {
+        let query = format!(
+            r#"SELECT {} FROM documents
+               WHERE user_id = $1 AND {} AND ($2::uuid IS NULL OR id > $2)
+                 AND NOT EXISTS (SELECT 1 FROM document_page_hashes h WHERE h.document_id = documents.id)
+               ORDER BY id LIMIT $3"#,
+            DOCUMENT_FIELDS, HASHABLE_DOCUMENTS
+        );
+        let rows = sqlx::query(&query)
+            .bind(user_id)
+            .bind(after)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(map_row_to_document).collect())
+    }
+
+    pub async fn count_unhashed_documents(&self, user_id: Uuid) -> Result<i64> {
+        let query = format!(
+            r#"SELECT COUNT(*) FROM documents
+               WHERE user_id = $1 AND {}
+                 AND NOT EXISTS (SELECT 1 FROM document_page_hashes h WHERE h.document_id = documents.id)"#,
+            HASHABLE_DOCUMENTS
+        );
+        let count = sqlx::query_scalar::<_, i64>(&query)
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Document pairs the user marked as not duplicates, smaller ID first
+    pub async fn get_similar_document_dismissals(&self, user_id: Uuid) -> Result<HashSet<(Uuid, Uuid)>> {
+        let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+            "SELECT document_a, document_b FROM similar_document_dismissals WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Mark every pair of the documents as not duplicates
+    pub async fn dismiss_similar_documents(&self, user_id: Uuid, document_ids: &[Uuid]) -> Result<u64> {
+        let result = sqlx::query(
+            r#"INSERT INTO similar_document_dismissals (user_id, document_a, document_b)
+               SELECT $1, a.id, b.id
+               FROM documents a JOIN documents b ON a.id < b.id
+               WHERE a.id = ANY($2) AND b.id = ANY($2) AND a.user_id = $1 AND b.user_id = $1
+               ON CONFLICT DO NOTHING"#
+        )
+        .bind(user_id)
+        .bind(document_ids)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Copy labels, tags and the physical location of duplicates onto the document
+    /// that is kept. Returns the number of labels added.
+    pub async fn merge_duplicate_metadata(&self, keep_id: Uuid, duplicate_ids: &[Uuid], assigned_by: Uuid) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+
+        let labels = sqlx::query(
+            r#"INSERT INTO document_labels (document_id, label_id, assigned_by)
+               SELECT DISTINCT $1::uuid, label_id, $3::uuid FROM document_labels WHERE document_id = ANY($2)
+               ON CONFLICT DO NOTHING"#
+        )
+        .bind(keep_id)
+        .bind(duplicate_ids)
+        .bind(assigned_by)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"UPDATE documents SET tags = ARRAY(
+                   SELECT DISTINCT tag FROM (
+                       SELECT unnest(tags) AS tag FROM documents WHERE id = $1 OR id = ANY($2)
+                   ) all_tags ORDER BY tag
+               )
+               WHERE id = $1"#
+        )
+        .bind(keep_id)
+        .bind(duplicate_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"INSERT INTO document_physical_locations (document_id, location_id)
+               SELECT $1, location_id FROM document_physical_locations
+               WHERE document_id = ANY($2)
+               ORDER BY assigned_at
+               LIMIT 1
+               ON CONFLICT (document_id) DO NOTHING"#
+        )
+        .bind(keep_id)
+        .bind(duplicate_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(labels.rows_affected())
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::SourceFilenameTemplates;
+
+impl Database {
+    pub async fn get_source_filename_templates(&self, source_id: Uuid) -> Result<Option<SourceFilenameTemplates>> {
+        let templates = sqlx::query_as::<_, SourceFilenameTemplates>(
+            "SELECT source_id, templates, updated_at FROM source_filename_templates WHERE source_id = $1",
+        )
+        .bind(source_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(templates)
+    }
+
+    /// Replace the filename templates of a source; no templates removes them
+    pub async fn set_source_filename_templates(
+        &self,
+        source_id: Uuid,
+        templates: &[String],
+    ) -> Result<Option<SourceFilenameTemplates>> {
+        if templates.is_empty() {
+            sqlx::query("DELETE FROM source_filename_templates WHERE source_id = $1")
+                .bind(source_id)
+                .execute(&self.pool)
+                .await?;
+            return Ok(None);
+        }
+
+        let templates = sqlx::query_as::<_, SourceFilenameTemplates>(
+            r#"INSERT INTO source_filename_templates (source_id, templates)
+               VALUES ($1, $2)
+               ON CONFLICT (source_id) DO UPDATE
+               SET templates = EXCLUDED.templates, updated_at = NOW()
+               RETURNING source_id, templates, updated_at"#,
+        )
+        .bind(source_id)
+        .bind(templates)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Some(templates))
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use chrono::NaiveTime;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::SourceSchedule;
+
+const SOURCE_SCHEDULE_COLUMNS: &str = "source_id, cron_expression, quiet_hours_start, quiet_hours_end, \
+     max_runtime_minutes, last_sync_started_at, last_sync_duration_ms, updated_at";
+
+impl Database {
+    pub async fn get_source_schedule(&self, source_id: Uuid) -> Result<Option<SourceSchedule>> {
+        let query = format!("SELECT {} FROM source_schedules WHERE source_id = $1", SOURCE_SCHEDULE_COLUMNS);
+        let schedule = sqlx::query_as::<_, SourceSchedule>(&query)
+            .bind(source_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn get_source_schedules(&self, source_ids: &[Uuid]) -> Result<Vec<SourceSchedule>> {
+        let query = format!("SELECT {} FROM source_schedules WHERE source_id = ANY($1)", SOURCE_SCHEDULE_COLUMNS);
+        let schedules = sqlx::query_as::<_, SourceSchedule>(&query)
+            .bind(source_ids)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(schedules)
+    }
+
+    /// Replace when a source syncs; the timings of its last sync are kept
+    pub async fn set_source_schedule(
+        &self,
+        source_id: Uuid,
+        cron_expression: Option<&str>,
+        quiet_hours: Option<(NaiveTime, NaiveTime)>,
+        max_runtime_minutes: Option<i32>,
+    ) -> Result<SourceSchedule> {
+        let query = format!(
+            r#"INSERT INTO source_schedules (source_id, cron_expression, quiet_hours_start, quiet_hours_end, max_runtime_minutes)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (source_id) DO UPDATE
+               SET cron_expression = EXCLUDED.cron_expression,
+                   quiet_hours_start = EXCLUDED.quiet_hours_start,
+                   quiet_hours_end = EXCLUDED.quiet_hours_end,
+                   max_runtime_minutes = EXCLUDED.max_runtime_minutes,
+                   updated_at = NOW()
+               RETURNING {}"#,
+            SOURCE_SCHEDULE_COLUMNS
+        );
+        let schedule = sqlx::query_as::<_, SourceSchedule>(&query)
+            .bind(source_id)
+            .bind(cron_expression)
+            .bind(quiet_hours.map(|(start, _)| start))
+            .bind(quiet_hours.map(|(_, end)| end))
+            .bind(max_runtime_minutes)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(schedule)
+    }
+
+    /// Note that a sync of the source started, so a cron schedule counts from it
+    pub async fn record_source_sync_started(&self, source_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO source_schedules (source_id, last_sync_started_at)
+               VALUES ($1, NOW())
+               ON CONFLICT (source_id) DO UPDATE
+               SET last_sync_started_at = NOW(), updated_at = NOW()"#
+        )
+        .bind(source_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_source_sync_duration(&self, source_id: Uuid, duration_ms: i64) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO source_schedules (source_id, last_sync_duration_ms)
+               VALUES ($1, $2)
+               ON CONFLICT (source_id) DO UPDATE
+               SET last_sync_duration_ms = EXCLUDED.last_sync_duration_ms, updated_at = NOW()"#
+        )
+        .bind(source_id)
+        .bind(duration_ms)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{SourceSyncRun, SourceSyncRunOutcome};
+use crate::scheduling::sync_runs::RUNS_KEPT_PER_SOURCE;
+
+const SOURCE_SYNC_RUN_COLUMNS: &str = "id, source_id, status, started_at, finished_at, files_added, files_updated, \
+     files_skipped, files_failed, error, file_errors";
+
+impl Database {
+    /// Add a running sync to the history of a source, dropping its oldest runs
+    pub async fn start_source_sync_run(&self, source_id: Uuid) -> Result<Uuid> {
+        let run_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO source_sync_runs (source_id) VALUES ($1) RETURNING id"
+        )
+        .bind(source_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"DELETE FROM source_sync_runs
+               WHERE source_id = $1
+                 AND id NOT IN (
+                     SELECT id FROM source_sync_runs
+                     WHERE source_id = $1
+                     ORDER BY started_at DESC
+                     LIMIT $2
+                 )"#
+        )
+        .bind(source_id)
+        .bind(RUNS_KEPT_PER_SOURCE)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(run_id)
+    }
+
+    pub async fn finish_source_sync_run(&self, run_id: Uuid, outcome: &SourceSyncRunOutcome) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE source_sync_runs
+               SET status = $2, finished_at = NOW(), error = $3,
+                   files_added = $4, files_updated = $5, files_skipped = $6, files_failed = $7,
+                   file_errors = $8
+               WHERE id = $1"#
+        )
+        .bind(run_id)
+        .bind(outcome.status)
+        .bind(&outcome.error)
+        .bind(outcome.files_added)
+        .bind(outcome.files_updated)
+        .bind(outcome.files_skipped)
+        .bind(outcome.files_failed)
+        .bind(sqlx::types::Json(&outcome.file_errors))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Runs of a source, newest first
+    pub async fn list_source_sync_runs(&self, source_id: Uuid, limit: i64, offset: i64) -> Result<Vec<SourceSyncRun>> {
+        let query = format!(
+            "SELECT {} FROM source_sync_runs WHERE source_id = $1 ORDER BY started_at DESC LIMIT $2 OFFSET $3",
+            SOURCE_SYNC_RUN_COLUMNS
+        );
+        let runs = sqlx::query_as::<_, SourceSyncRun>(&query)
+            .bind(source_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(runs)
+    }
+
+    /// Close the runs a server restart cut short, leaving those of sources still syncing
+    /// on another node
+    pub async fn mark_source_sync_runs_interrupted(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"UPDATE source_sync_runs
+               SET status = 'interrupted', finished_at = NOW(), error = 'Interrupted by a server restart'
+               WHERE status = 'running'
+                 AND source_id NOT IN (SELECT id FROM sources WHERE status = 'syncing')"#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
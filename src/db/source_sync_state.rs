@@ -0,0 +1,64 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::SourceSyncState;
+
+impl Database {
+    pub async fn get_source_sync_state(&self, source_id: Uuid) -> Result<Option<SourceSyncState>> {
+        let state = sqlx::query_as::<_, SourceSyncState>(
+            r#"SELECT source_id, delta_cursor, access_token, access_token_expires_at, refresh_token, updated_at
+               FROM source_sync_state WHERE source_id = $1"#
+        )
+        .bind(source_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(state)
+    }
+
+    /// Store the cursor to resume from; None forces the next sync to list everything
+    pub async fn save_source_delta_cursor(&self, source_id: Uuid, cursor: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO source_sync_state (source_id, delta_cursor)
+               VALUES ($1, $2)
+               ON CONFLICT (source_id) DO UPDATE
+               SET delta_cursor = EXCLUDED.delta_cursor, updated_at = NOW()"#
+        )
+        .bind(source_id)
+        .bind(cursor)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store freshly issued tokens. A provider that did not rotate the refresh
+    /// token passes None and the stored one is kept.
+    pub async fn save_source_tokens(
+        &self,
+        source_id: Uuid,
+        access_token: &str,
+        expires_at: DateTime<Utc>,
+        refresh_token: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO source_sync_state (source_id, access_token, access_token_expires_at, refresh_token)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (source_id) DO UPDATE
+               SET access_token = EXCLUDED.access_token,
+                   access_token_expires_at = EXCLUDED.access_token_expires_at,
+                   refresh_token = COALESCE(EXCLUDED.refresh_token, source_sync_state.refresh_token),
+                   updated_at = NOW()"#
+        )
+        .bind(source_id)
+        .bind(access_token)
+        .bind(expires_at)
+        .bind(refresh_token)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,15 +1,18 @@
 use anyhow::Result;
-use chrono::Utc;
-use sqlx::Row;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, Row};
 use uuid::Uuid;
 use tracing::{info, warn, error};
 
-use super::Database;
+use super::{workspaces::WorkspaceScoped, Database};
+use crate::services::encryption;
+use crate::utils::pagination::PageRequest;
 
 impl Database {
     pub async fn create_source(&self, user_id: Uuid, source: &crate::models::CreateSource) -> Result<crate::models::Source> {
         let id = Uuid::new_v4();
         let now = Utc::now();
+        let config = seal_source_secrets(user_id, None, &source.config).await?;
         
         let row = sqlx::query(
             r#"INSERT INTO sources (id, user_id, name, source_type, enabled, config, status, created_at, updated_at)
@@ -21,13 +24,13 @@ impl Database {
         .bind(&source.name)
         .bind(source.source_type.to_string())
         .bind(source.enabled.unwrap_or(true))
-        .bind(&source.config)
+        .bind(&config)
         .bind(now)
         .bind(now)
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(crate::models::Source {
+        open_source_secrets(crate::models::Source {
             id: row.get("id"),
             user_id: row.get("user_id"),
             name: row.get("name"),
@@ -47,7 +50,7 @@ impl Database {
             last_validation_at: row.get("last_validation_at"),
             validation_score: row.get("validation_score"),
             validation_issues: row.get("validation_issues"),
-        })
+        }).await
     }
 
     pub async fn get_source(&self, user_id: Uuid, source_id: Uuid) -> Result<Option<crate::models::Source>> {
@@ -60,7 +63,7 @@ impl Database {
         .await?;
 
         match row {
-            Some(row) => Ok(Some(crate::models::Source {
+            Some(row) => Ok(Some(open_source_secrets(crate::models::Source {
                 id: row.get("id"),
                 user_id: row.get("user_id"),
                 name: row.get("name"),
@@ -80,7 +83,7 @@ impl Database {
                 last_validation_at: row.get("last_validation_at"),
                 validation_score: row.get("validation_score"),
                 validation_issues: row.get("validation_issues"),
-            })),
+            }).await?)),
             None => Ok(None),
         }
     }
@@ -93,32 +96,35 @@ impl Database {
         .fetch_all(&self.pool)
         .await?;
 
-        let mut sources = Vec::new();
-        for row in rows {
-            sources.push(crate::models::Source {
-                id: row.get("id"),
-                user_id: row.get("user_id"),
-                name: row.get("name"),
-                source_type: row.get::<String, _>("source_type").try_into().map_err(|e: String| anyhow::anyhow!(e))?,
-                enabled: row.get("enabled"),
-                config: row.get("config"),
-                status: row.get::<String, _>("status").try_into().map_err(|e: String| anyhow::anyhow!(e))?,
-                last_sync_at: row.get("last_sync_at"),
-                last_error: row.get("last_error"),
-                last_error_at: row.get("last_error_at"),
-                total_files_synced: row.get("total_files_synced"),
-                total_files_pending: row.get("total_files_pending"),
-                total_size_bytes: row.get("total_size_bytes"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                validation_status: row.get("validation_status"),
-                last_validation_at: row.get("last_validation_at"),
-                validation_score: row.get("validation_score"),
-                validation_issues: row.get("validation_issues"),
-            });
-        }
+        open_sources_secrets(rows.iter().map(source_from_row).collect::<Result<_>>()?).await
+    }
+
+    /// A page of the user's sources, newest first with the id breaking ties
+    pub async fn get_sources_page(&self, user_id: Uuid, page: &PageRequest<DateTime<Utc>>) -> Result<Vec<crate::models::Source>> {
+        let (after_created_at, after_id) = page.after.as_ref().map(|after| (after.key, after.id)).unzip();
+        let rows = sqlx::query(
+            r#"SELECT * FROM sources
+               WHERE user_id = $1
+                 AND ($2::timestamptz IS NULL OR (created_at, id) < ($2, $3))
+               ORDER BY created_at DESC, id DESC
+               LIMIT $4"#
+        )
+        .bind(user_id)
+        .bind(after_created_at)
+        .bind(after_id)
+        .bind(page.fetch_limit())
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(sources)
+        open_sources_secrets(rows.iter().map(source_from_row).collect::<Result<_>>()?).await
+    }
+
+    pub async fn count_sources(&self, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM sources WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count)
     }
 
     pub async fn update_source(&self, user_id: Uuid, source_id: Uuid, update: &crate::models::UpdateSource) -> Result<crate::models::Source> {
@@ -143,6 +149,11 @@ impl Database {
         bind_count += 1;
         query.push_str(&format!(" AND user_id = ${} RETURNING *", bind_count));
 
+        let config = match &update.config {
+            Some(config) => Some(seal_source_secrets(user_id, Some(source_id), config).await?),
+            None => None,
+        };
+
         let mut query_builder = sqlx::query(&query);
 
         // Bind values in order
@@ -152,7 +163,7 @@ impl Database {
         if let Some(enabled) = &update.enabled {
             query_builder = query_builder.bind(enabled);
         }
-        if let Some(config) = &update.config {
+        if let Some(config) = &config {
             query_builder = query_builder.bind(config);
         }
         query_builder = query_builder.bind(source_id);
@@ -160,7 +171,7 @@ impl Database {
 
         let row = query_builder.fetch_one(&self.pool).await?;
 
-        Ok(crate::models::Source {
+        open_source_secrets(crate::models::Source {
             id: row.get("id"),
             user_id: row.get("user_id"),
             name: row.get("name"),
@@ -180,7 +191,39 @@ impl Database {
             last_validation_at: row.get("last_validation_at"),
             validation_score: row.get("validation_score"),
             validation_issues: row.get("validation_issues"),
-        })
+        }).await
+    }
+
+    /// Seal the credentials of a source with the active version of its key, e.g. after a
+    /// rotation. Returns false when they already were or encryption is disabled.
+    pub async fn reseal_source_secrets(&self, source_id: Uuid) -> Result<bool> {
+        let Some(encryption) = encryption::installed() else {
+            return Ok(false);
+        };
+        let Some((user_id, mut config)) = sqlx::query_as::<_, (Uuid, serde_json::Value)>(
+            "SELECT user_id, config FROM sources WHERE id = $1"
+        )
+        .bind(source_id)
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(false);
+        };
+
+        let owner = encryption.key_owner(user_id, Some((WorkspaceScoped::Source, source_id))).await?;
+        let active_key = encryption.active_key(owner).await?;
+        if encryption::secrets_sealed_with(&config, active_key.id) {
+            return Ok(false);
+        }
+        encryption.open_secrets(&mut config).await?;
+        encryption.seal_secrets(owner, &mut config).await?;
+
+        sqlx::query("UPDATE sources SET config = $2 WHERE id = $1")
+            .bind(source_id)
+            .bind(&config)
+            .execute(&self.pool)
+            .await?;
+        Ok(true)
     }
 
     pub async fn delete_source(&self, user_id: Uuid, source_id: Uuid) -> Result<bool> {
@@ -277,7 +320,7 @@ impl Database {
             });
         }
 
-        Ok(sources)
+        open_sources_secrets(sources).await
     }
 
     pub async fn get_sources_for_sync(&self) -> Result<Vec<crate::models::Source>> {
@@ -361,7 +404,7 @@ impl Database {
             sources.push(source);
         }
 
-        Ok(sources)
+        open_sources_secrets(sources).await
     }
 
     pub async fn get_source_by_id(&self, source_id: Uuid) -> Result<Option<crate::models::Source>> {
@@ -377,7 +420,7 @@ impl Database {
         .await?;
 
         if let Some(row) = row {
-            Ok(Some(crate::models::Source {
+            Ok(Some(open_source_secrets(crate::models::Source {
                 id: row.get("id"),
                 user_id: row.get("user_id"),
                 name: row.get("name"),
@@ -399,7 +442,7 @@ impl Database {
                 last_validation_at: row.get("last_validation_at"),
                 validation_score: row.get("validation_score"),
                 validation_issues: row.get("validation_issues"),
-            }))
+            }).await?))
         } else {
             Ok(None)
         }
@@ -477,8 +520,9 @@ impl Database {
     }
 
     /// Atomically start a sync operation by checking current status and updating to syncing
-    /// Returns true if sync was successfully started, false if already syncing or error
-    pub async fn start_sync_atomic(&self, source_id: Uuid) -> Result<bool> {
+    /// Returns true if sync was successfully started, false if already syncing or error.
+    /// The sync is recorded as run by `node_id`, whose restart alone resets it.
+    pub async fn start_sync_atomic(&self, source_id: Uuid, node_id: Uuid) -> Result<bool> {
         let mut tx = self.pool.begin().await?;
         
         // Check current status - only allow starting sync from idle or error states
@@ -502,11 +546,12 @@ impl Database {
             // Update to syncing status
             let affected_rows = sqlx::query(
                 r#"UPDATE sources 
-                   SET status = 'syncing', last_error = NULL, last_error_at = NULL, updated_at = NOW()
+                   SET status = 'syncing', sync_node_id = $3, last_error = NULL, last_error_at = NULL, updated_at = NOW()
                    WHERE id = $1 AND status = $2"#
             )
             .bind(source_id)
             .bind(status_str)
+            .bind(node_id)
             .execute(&mut *tx)
             .await?
             .rows_affected();
@@ -626,7 +671,8 @@ impl Database {
         }
     }
 
-    /// Reset stuck syncing sources back to idle (for cleanup during startup)
+    /// Reset stuck syncing sources back to idle (for cleanup during startup). Syncs run
+    /// by nodes still online are left to them.
     pub async fn reset_stuck_syncing_sources(&self) -> Result<u64> {
         let affected_rows = sqlx::query(
             r#"UPDATE sources 
@@ -634,7 +680,8 @@ impl Database {
                    last_error = 'Sync was interrupted by server restart', 
                    last_error_at = NOW(), 
                    updated_at = NOW()
-               WHERE status = 'syncing'"#
+               WHERE status = 'syncing'
+                 AND (sync_node_id IS NULL OR sync_node_id NOT IN (SELECT id FROM live_worker_nodes))"#
         )
         .execute(&self.pool)
         .await?
@@ -646,4 +693,71 @@ impl Database {
         
         Ok(affected_rows)
     }
-}
\ No newline at end of file
+
+    /// Source path to document ID and recorded ETag for the documents a source has ingested
+    pub async fn get_source_document_paths(&self, source_id: Uuid) -> Result<std::collections::HashMap<String, (Uuid, Option<String>)>> {
+        let rows = sqlx::query_as::<_, (String, Uuid, Option<String>)>(
+            r#"SELECT source_path, id, source_metadata->>'etag'
+               FROM documents
+               WHERE source_id = $1 AND source_path IS NOT NULL"#
+        )
+        .bind(source_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(path, id, etag)| (path, (id, etag))).collect())
+    }
+}
+
+/// The source configuration with its credentials sealed, when encryption is enabled, with
+/// the key of the source's workspace or owner
+async fn seal_source_secrets(user_id: Uuid, source_id: Option<Uuid>, config: &serde_json::Value) -> Result<serde_json::Value> {
+    let mut config = config.clone();
+    if let Some(encryption) = encryption::installed() {
+        let owner = encryption
+            .key_owner(user_id, source_id.map(|id| (WorkspaceScoped::Source, id)))
+            .await?;
+        encryption.seal_secrets(owner, &mut config).await?;
+    }
+    Ok(config)
+}
+
+/// The source with its credentials opened for use
+async fn open_source_secrets(mut source: crate::models::Source) -> Result<crate::models::Source> {
+    if let Some(encryption) = encryption::installed() {
+        encryption.open_secrets(&mut source.config).await?;
+    }
+    Ok(source)
+}
+
+async fn open_sources_secrets(sources: Vec<crate::models::Source>) -> Result<Vec<crate::models::Source>> {
+    let mut opened = Vec::with_capacity(sources.len());
+    for source in sources {
+        opened.push(open_source_secrets(source).await?);
+    }
+    Ok(opened)
+}
+
+fn source_from_row(row: &PgRow) -> Result<crate::models::Source> {
+    Ok(crate::models::Source {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        name: row.get("name"),
+        source_type: row.get::<String, _>("source_type").try_into().map_err(|e: String| anyhow::anyhow!(e))?,
+        enabled: row.get("enabled"),
+        config: row.get("config"),
+        status: row.get::<String, _>("status").try_into().map_err(|e: String| anyhow::anyhow!(e))?,
+        last_sync_at: row.get("last_sync_at"),
+        last_error: row.get("last_error"),
+        last_error_at: row.get("last_error_at"),
+        total_files_synced: row.get("total_files_synced"),
+        total_files_pending: row.get("total_files_pending"),
+        total_size_bytes: row.get("total_size_bytes"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        validation_status: row.get("validation_status"),
+        last_validation_at: row.get("last_validation_at"),
+        validation_score: row.get("validation_score"),
+        validation_issues: row.get("validation_issues"),
+    })
+}
@@ -0,0 +1,242 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    CorrespondentCount, Dashboard, DashboardWidget, IngestionBucket, LabelCount, OcrBucket, StatisticsBucket,
+    UserStatistics,
+};
+
+/// Pages of a document's OCR text, which separates them with form feeds
+const OCR_PAGES: &str = r"(COALESCE(LENGTH(d.ocr_text) - LENGTH(REPLACE(d.ocr_text, E'\f', '')), 0) + 1)";
+
+/// Documents of user $1, or of everyone when it is null, uploaded from day $2 to day
+/// $3 (UTC, both included)
+const IN_RANGE: &str = r#"($1::uuid IS NULL OR d.user_id = $1)
+    AND d.created_at >= ($2::date)::timestamp AT TIME ZONE 'UTC'
+    AND d.created_at < ($3::date + 1)::timestamp AT TIME ZONE 'UTC'"#;
+
+impl Database {
+    /// Documents and bytes uploaded per bucket, with empty buckets
+    pub async fn get_ingestion_statistics(
+        &self,
+        user_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+        bucket: StatisticsBucket,
+    ) -> Result<Vec<IngestionBucket>> {
+        let query = format!(
+            r#"WITH buckets AS (
+                   SELECT generate_series(date_trunc($4, $2::date::timestamp), $3::date::timestamp, ('1 ' || $4)::interval)::date AS start
+               )
+               SELECT b.start, COUNT(d.id) AS documents, COALESCE(SUM(d.file_size), 0)::bigint AS bytes
+               FROM buckets b
+               LEFT JOIN documents d
+                 ON date_trunc($4, d.created_at AT TIME ZONE 'UTC')::date = b.start AND {}
+               GROUP BY b.start
+               ORDER BY b.start"#,
+            IN_RANGE
+        );
+        let buckets = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, IngestionBucket>(&query)
+                    .bind(user_id)
+                    .bind(from)
+                    .bind(to)
+                    .bind(bucket.to_string());
+                async move { query.fetch_all(&pool).await }
+            })
+            .await?;
+
+        Ok(buckets)
+    }
+
+    /// Documents and pages whose OCR completed per bucket, with empty buckets
+    pub async fn get_ocr_statistics(
+        &self,
+        user_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+        bucket: StatisticsBucket,
+    ) -> Result<Vec<OcrBucket>> {
+        let query = format!(
+            r#"WITH buckets AS (
+                   SELECT generate_series(date_trunc($4, $2::date::timestamp), $3::date::timestamp, ('1 ' || $4)::interval)::date AS start
+               )
+               SELECT b.start, COUNT(d.id) AS documents, COALESCE(SUM({}), 0)::bigint AS pages
+               FROM buckets b
+               LEFT JOIN documents d
+                 ON date_trunc($4, d.ocr_completed_at AT TIME ZONE 'UTC')::date = b.start
+                AND d.ocr_status = 'completed'
+                AND ($1::uuid IS NULL OR d.user_id = $1)
+                AND d.ocr_completed_at >= ($2::date)::timestamp AT TIME ZONE 'UTC'
+                AND d.ocr_completed_at < ($3::date + 1)::timestamp AT TIME ZONE 'UTC'
+               GROUP BY b.start
+               ORDER BY b.start"#,
+            OCR_PAGES
+        );
+        let buckets = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, OcrBucket>(&query)
+                    .bind(user_id)
+                    .bind(from)
+                    .bind(to)
+                    .bind(bucket.to_string());
+                async move { query.fetch_all(&pool).await }
+            })
+            .await?;
+
+        Ok(buckets)
+    }
+
+    /// Bytes of the documents still stored that were uploaded before day `from`
+    pub async fn get_stored_bytes_before(&self, user_id: Option<Uuid>, from: NaiveDate) -> Result<i64> {
+        let bytes = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_scalar::<_, i64>(
+                    r#"SELECT COALESCE(SUM(file_size), 0)::bigint FROM documents
+                       WHERE ($1::uuid IS NULL OR user_id = $1)
+                         AND created_at < ($2::date)::timestamp AT TIME ZONE 'UTC'"#,
+                )
+                .bind(user_id)
+                .bind(from);
+                async move { query.fetch_one(&pool).await }
+            })
+            .await?;
+
+        Ok(bytes)
+    }
+
+    /// Correspondents of the most documents uploaded in the range
+    pub async fn get_top_correspondent_statistics(
+        &self,
+        user_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+        limit: i64,
+    ) -> Result<Vec<CorrespondentCount>> {
+        let query = format!(
+            r#"SELECT c.id AS correspondent_id, c.name, COUNT(*) AS documents
+               FROM documents d
+               JOIN document_correspondents dc ON dc.document_id = d.id
+               JOIN correspondents c ON c.id = dc.correspondent_id
+               WHERE {}
+               GROUP BY c.id, c.name
+               ORDER BY documents DESC, c.name
+               LIMIT $4"#,
+            IN_RANGE
+        );
+        let correspondents = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, CorrespondentCount>(&query)
+                    .bind(user_id)
+                    .bind(from)
+                    .bind(to)
+                    .bind(limit);
+                async move { query.fetch_all(&pool).await }
+            })
+            .await?;
+
+        Ok(correspondents)
+    }
+
+    /// Labels of the most documents uploaded in the range
+    pub async fn get_label_statistics(
+        &self,
+        user_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+        limit: i64,
+    ) -> Result<Vec<LabelCount>> {
+        let query = format!(
+            r#"SELECT l.id AS label_id, l.name, l.color, COUNT(*) AS documents
+               FROM documents d
+               JOIN document_labels dl ON dl.document_id = d.id
+               JOIN labels l ON l.id = dl.label_id
+               WHERE {}
+               GROUP BY l.id, l.name, l.color
+               ORDER BY documents DESC, l.name
+               LIMIT $4"#,
+            IN_RANGE
+        );
+        let labels = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, LabelCount>(&query)
+                    .bind(user_id)
+                    .bind(from)
+                    .bind(to)
+                    .bind(limit);
+                async move { query.fetch_all(&pool).await }
+            })
+            .await?;
+
+        Ok(labels)
+    }
+
+    /// Every user's uploads and OCR pages in the range next to what they store, or only
+    /// the given user's; most documents first
+    pub async fn get_user_statistics(
+        &self,
+        user_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<UserStatistics>> {
+        let query = format!(
+            r#"SELECT u.id AS user_id, u.username,
+                      COUNT(d.id) FILTER (WHERE {range}) AS documents,
+                      COALESCE(SUM(d.file_size) FILTER (WHERE {range}), 0)::bigint AS bytes,
+                      COALESCE(SUM({pages}) FILTER (
+                          WHERE d.ocr_status = 'completed'
+                            AND d.ocr_completed_at >= ($2::date)::timestamp AT TIME ZONE 'UTC'
+                            AND d.ocr_completed_at < ($3::date + 1)::timestamp AT TIME ZONE 'UTC'
+                      ), 0)::bigint AS ocr_pages,
+                      COUNT(d.id) AS total_documents,
+                      COALESCE(SUM(d.file_size), 0)::bigint AS total_bytes
+               FROM users u
+               LEFT JOIN documents d ON d.user_id = u.id
+               WHERE $1::uuid IS NULL OR u.id = $1
+               GROUP BY u.id, u.username
+               ORDER BY documents DESC, total_documents DESC, u.username"#,
+            range = IN_RANGE,
+            pages = OCR_PAGES
+        );
+        let users = self
+            .read_with_fallback(|pool| {
+                let query = sqlx::query_as::<_, UserStatistics>(&query)
+                    .bind(user_id)
+                    .bind(from)
+                    .bind(to);
+                async move { query.fetch_all(&pool).await }
+            })
+            .await?;
+
+        Ok(users)
+    }
+
+    pub async fn get_user_dashboard(&self, user_id: Uuid) -> Result<Option<Dashboard>> {
+        let row = sqlx::query_as::<_, (sqlx::types::Json<Vec<DashboardWidget>>, chrono::DateTime<chrono::Utc>)>(
+            "SELECT widgets, updated_at FROM user_dashboards WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|(widgets, updated_at)| Dashboard { widgets: widgets.0, updated_at: Some(updated_at) }))
+    }
+
+    pub async fn save_user_dashboard(&self, user_id: Uuid, widgets: &[DashboardWidget]) -> Result<Dashboard> {
+        let updated_at = sqlx::query_scalar::<_, chrono::DateTime<chrono::Utc>>(
+            r#"INSERT INTO user_dashboards (user_id, widgets, updated_at)
+               VALUES ($1, $2, NOW())
+               ON CONFLICT (user_id) DO UPDATE SET widgets = EXCLUDED.widgets, updated_at = NOW()
+               RETURNING updated_at"#
+        )
+        .bind(user_id)
+        .bind(sqlx::types::Json(widgets))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Dashboard { widgets: widgets.to_vec(), updated_at: Some(updated_at) })
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::Result;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+use super::documents::{map_row_to_document, DOCUMENT_FIELDS};
+use super::Database;
+use crate::models::{
+    CompressedOriginal, CreateStorageCompressionPolicyRequest, Document, StorageCompressionPolicy,
+    StorageCompressionSummary, UpdateStorageCompressionPolicyRequest,
+};
+
+/// The recompressed contents replacing a document's file
+pub struct CompressedContents<'a> {
+    pub filename: &'a str,
+    pub original_filename: &'a str,
+    pub file_path: &'a str,
+    pub file_size: i64,
+    pub file_hash: &'a str,
+    pub mime_type: &'a str,
+}
+
+impl Database {
+    pub async fn get_storage_compression_policies(&self) -> Result<Vec<StorageCompressionPolicy>> {
+        let policies = sqlx::query_as::<_, StorageCompressionPolicy>(
+            "SELECT * FROM storage_compression_policies ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(policies)
+    }
+
+    pub async fn get_storage_compression_policy(&self, id: Uuid) -> Result<Option<StorageCompressionPolicy>> {
+        let policy = sqlx::query_as::<_, StorageCompressionPolicy>(
+            "SELECT * FROM storage_compression_policies WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(policy)
+    }
+
+    pub async fn create_storage_compression_policy(
+        &self,
+        request: &CreateStorageCompressionPolicyRequest,
+        created_by: Uuid,
+    ) -> Result<StorageCompressionPolicy> {
+        let policy = sqlx::query_as::<_, StorageCompressionPolicy>(
+            r#"INSERT INTO storage_compression_policies (
+                   name, enabled, mime_types, min_file_size_bytes, target_format, target_dpi,
+                   jpeg_quality, keep_original_days, created_by
+               )
+               VALUES ($1, COALESCE($2, TRUE), $3, COALESCE($4, 0), $5, COALESCE($6, 200), COALESCE($7, 75), $8, $9)
+               RETURNING *"#,
+        )
+        .bind(request.name.trim())
+        .bind(request.enabled)
+        .bind(&request.mime_types)
+        .bind(request.min_file_size_bytes)
+        .bind(request.target_format.to_string())
+        .bind(request.target_dpi)
+        .bind(request.jpeg_quality)
+        .bind(request.keep_original_days)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(policy)
+    }
+
+    pub async fn update_storage_compression_policy(
+        &self,
+        id: Uuid,
+        request: &UpdateStorageCompressionPolicyRequest,
+    ) -> Result<Option<StorageCompressionPolicy>> {
+        let policy = sqlx::query_as::<_, StorageCompressionPolicy>(
+            r#"UPDATE storage_compression_policies
+               SET name = COALESCE($2, name),
+                   enabled = COALESCE($3, enabled),
+                   mime_types = COALESCE($4, mime_types),
+                   min_file_size_bytes = COALESCE($5, min_file_size_bytes),
+                   target_format = COALESCE($6, target_format),
+                   target_dpi = COALESCE($7, target_dpi),
+                   jpeg_quality = COALESCE($8, jpeg_quality),
+                   keep_original_days = CASE WHEN $10 THEN NULL ELSE COALESCE($9, keep_original_days) END,
+                   updated_at = NOW()
+               WHERE id = $1
+               RETURNING *"#,
+        )
+        .bind(id)
+        .bind(request.name.as_deref().map(str::trim))
+        .bind(request.enabled)
+        .bind(&request.mime_types)
+        .bind(request.min_file_size_bytes)
+        .bind(request.target_format.map(|format| format.to_string()))
+        .bind(request.target_dpi)
+        .bind(request.jpeg_quality)
+        .bind(request.keep_original_days)
+        .bind(request.clear_keep_original_days)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(policy)
+    }
+
+    pub async fn delete_storage_compression_policy(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM storage_compression_policies WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Documents whose OCR completed and that were never recompressed or skipped, with a
+    /// file of one of the types and at least the size of an enabled policy, largest first.
+    /// Documents under a legal hold are left alone.
+    pub async fn get_storage_compression_candidates(&self, limit: i64) -> Result<Vec<Document>> {
+        let query = format!(
+            r#"SELECT {} FROM documents
+               WHERE ocr_status = 'completed'
+                 AND NOT EXISTS (SELECT 1 FROM compressed_originals c WHERE c.document_id = documents.id)
+                 AND NOT EXISTS (
+                     SELECT 1 FROM storage_compression_skips s
+                     WHERE s.document_id = documents.id AND s.file_hash IS NOT DISTINCT FROM documents.file_hash
+                 )
+                 AND NOT EXISTS (
+                     SELECT 1 FROM legal_hold_documents lhd JOIN legal_holds lh ON lh.id = lhd.hold_id
+                     WHERE lhd.document_id = documents.id AND lh.released_at IS NULL
+                 )
+                 AND EXISTS (
+                     SELECT 1 FROM storage_compression_policies p, unnest(p.mime_types) AS t(mime_type)
+                     WHERE p.enabled
+                       AND documents.file_size >= p.min_file_size_bytes
+                       AND (documents.mime_type = t.mime_type
+                            OR (t.mime_type LIKE '%/*' AND documents.mime_type LIKE replace(t.mime_type, '*', '%')))
+                 )
+               ORDER BY file_size DESC
+               LIMIT $1"#,
+            DOCUMENT_FIELDS
+        );
+        let rows = sqlx::query(&query).bind(limit).fetch_all(&self.pool).await?;
+
+        Ok(rows.iter().map(map_row_to_document).collect())
+    }
+
+    /// Point a document at its recompressed contents and keep its original for the
+    /// policy's window. Unlike a new version the OCR results stay: the text has not
+    /// changed, and word positions are relative to the page size.
+    pub async fn store_compressed_document(
+        &self,
+        document: &Document,
+        policy: &StorageCompressionPolicy,
+        original_id: Uuid,
+        original_file_path: &str,
+        contents: &CompressedContents<'_>,
+    ) -> Result<Document> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"INSERT INTO compressed_originals (
+                   document_id, id, policy_id, filename, original_filename, file_path, file_size, file_hash,
+                   mime_type, compressed_size, expires_at
+               )
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW() + make_interval(days => $11))"#,
+        )
+        .bind(document.id)
+        .bind(original_id)
+        .bind(policy.id)
+        .bind(&document.filename)
+        .bind(&document.original_filename)
+        .bind(original_file_path)
+        .bind(document.file_size)
+        .bind(&document.file_hash)
+        .bind(&document.mime_type)
+        .bind(contents.file_size)
+        .bind(policy.keep_original_days)
+        .execute(&mut *tx)
+        .await?;
+
+        let query = format!(
+            r#"UPDATE documents
+               SET filename = $2, original_filename = $3, file_path = $4, file_size = $5, file_hash = $6,
+                   mime_type = $7, updated_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            DOCUMENT_FIELDS
+        );
+        let row = sqlx::query(&query)
+            .bind(document.id)
+            .bind(contents.filename)
+            .bind(contents.original_filename)
+            .bind(contents.file_path)
+            .bind(contents.file_size)
+            .bind(contents.file_hash)
+            .bind(contents.mime_type)
+            .fetch_one(&mut *tx)
+            .await?;
+        // Pages rendered from the old file are rendered again on demand
+        sqlx::query("DELETE FROM page_artifacts WHERE document_id = $1")
+            .bind(document.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(map_row_to_document(&row))
+    }
+
+    /// Leave a document out of recompression until its content changes
+    pub async fn skip_storage_compression(&self, document: &Document, policy: &StorageCompressionPolicy) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO storage_compression_skips (document_id, policy_id, file_hash) VALUES ($1, $2, $3)
+               ON CONFLICT (document_id) DO UPDATE
+               SET policy_id = EXCLUDED.policy_id, file_hash = EXCLUDED.file_hash, skipped_at = NOW()"#,
+        )
+        .bind(document.id)
+        .bind(policy.id)
+        .bind(&document.file_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_compressed_original(&self, document_id: Uuid) -> Result<Option<CompressedOriginal>> {
+        let original = sqlx::query_as::<_, CompressedOriginal>(
+            "SELECT * FROM compressed_originals WHERE document_id = $1",
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(original)
+    }
+
+    /// Originals whose window ended and whose file was not deleted yet, with the id of
+    /// the user owning the document
+    pub async fn get_expired_compressed_originals(&self, limit: i64) -> Result<Vec<(CompressedOriginal, Uuid)>> {
+        let rows = sqlx::query(
+            r#"SELECT c.*, d.user_id AS owner_id
+               FROM compressed_originals c
+               JOIN documents d ON d.id = c.document_id
+               WHERE c.purged_at IS NULL AND c.expires_at <= NOW()
+               ORDER BY c.expires_at
+               LIMIT $1"#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Ok((CompressedOriginal::from_row(row)?, row.try_get("owner_id")?)))
+            .collect()
+    }
+
+    pub async fn mark_compressed_original_purged(&self, document_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE compressed_originals SET file_path = NULL, purged_at = NOW() WHERE document_id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_storage_compression_summary(&self) -> Result<StorageCompressionSummary> {
+        let summary = sqlx::query_as::<_, StorageCompressionSummary>(
+            r#"SELECT COUNT(*) AS documents,
+                      COALESCE(SUM(file_size), 0)::bigint AS original_bytes,
+                      COALESCE(SUM(compressed_size), 0)::bigint AS compressed_bytes,
+                      COUNT(*) FILTER (WHERE purged_at IS NULL) AS originals_kept,
+                      COALESCE(SUM(file_size) FILTER (WHERE purged_at IS NULL), 0)::bigint AS originals_kept_bytes
+               FROM compressed_originals"#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(summary)
+    }
+}
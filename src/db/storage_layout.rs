@@ -0,0 +1,196 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    RelocationCandidate, StorageLayout, StorageMigrationFailure, StorageMigrationStatus, StorageRelocation,
+};
+
+const STORAGE_RELOCATION_COLUMNS: &str = "id, template, status, total_documents, moved_documents, \
+     skipped_documents, failed_documents, last_document_id, failures, error, created_by, created_at, completed_at";
+
+/// Failures kept per relocation; the count goes on past it
+const MAX_RECORDED_FAILURES: i32 = 100;
+
+impl Database {
+    pub async fn get_storage_layout(&self) -> Result<StorageLayout> {
+        let layout = sqlx::query_as::<_, StorageLayout>(
+            "SELECT template, updated_by, updated_at FROM storage_layout WHERE id"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(layout.unwrap_or_default())
+    }
+
+    /// Save the layout template; None removes it
+    pub async fn set_storage_layout(&self, template: Option<&str>, updated_by: Uuid) -> Result<StorageLayout> {
+        let Some(template) = template else {
+            sqlx::query("DELETE FROM storage_layout").execute(&self.pool).await?;
+            return Ok(StorageLayout::default());
+        };
+
+        let layout = sqlx::query_as::<_, StorageLayout>(
+            r#"INSERT INTO storage_layout (id, template, updated_by, updated_at)
+               VALUES (TRUE, $1, $2, NOW())
+               ON CONFLICT (id) DO UPDATE
+               SET template = EXCLUDED.template, updated_by = EXCLUDED.updated_by, updated_at = NOW()
+               RETURNING template, updated_by, updated_at"#
+        )
+        .bind(template)
+        .bind(updated_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(layout)
+    }
+
+    pub async fn create_storage_relocation(&self, template: Option<&str>, created_by: Uuid) -> Result<StorageRelocation> {
+        let query = format!(
+            r#"INSERT INTO storage_relocations (template, total_documents, created_by)
+               VALUES ($1, (SELECT COUNT(*) FROM documents), $2)
+               RETURNING {}"#,
+            STORAGE_RELOCATION_COLUMNS
+        );
+
+        let relocation = sqlx::query_as::<_, StorageRelocation>(&query)
+            .bind(template)
+            .bind(created_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(relocation)
+    }
+
+    pub async fn get_storage_relocation(&self, id: Uuid) -> Result<Option<StorageRelocation>> {
+        let query = format!("SELECT {} FROM storage_relocations WHERE id = $1", STORAGE_RELOCATION_COLUMNS);
+        let relocation = sqlx::query_as::<_, StorageRelocation>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(relocation)
+    }
+
+    pub async fn list_storage_relocations(&self) -> Result<Vec<StorageRelocation>> {
+        let query = format!(
+            "SELECT {} FROM storage_relocations ORDER BY created_at DESC",
+            STORAGE_RELOCATION_COLUMNS
+        );
+        let relocations = sqlx::query_as::<_, StorageRelocation>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(relocations)
+    }
+
+    pub async fn get_running_storage_relocation(&self) -> Result<Option<StorageRelocation>> {
+        let query = format!(
+            "SELECT {} FROM storage_relocations WHERE status = 'running'",
+            STORAGE_RELOCATION_COLUMNS
+        );
+        let relocation = sqlx::query_as::<_, StorageRelocation>(&query)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(relocation)
+    }
+
+    /// Next batch of documents in id order, with what their layout path is made of
+    pub async fn get_documents_for_storage_relocation(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<RelocationCandidate>> {
+        let rows = sqlx::query_as::<_, RelocationCandidate>(
+            r#"SELECT d.id, d.user_id, u.username, d.filename, d.file_path,
+                      d.created_at, d.original_created_at,
+                      c.name AS correspondent, t.name AS document_type
+               FROM documents d
+               JOIN users u ON u.id = d.user_id
+               LEFT JOIN document_correspondents dc ON dc.document_id = d.id
+               LEFT JOIN correspondents c ON c.id = dc.correspondent_id
+               LEFT JOIN document_custom_fields dcf ON dcf.document_id = d.id
+               LEFT JOIN document_types t ON t.id = dcf.document_type_id
+               WHERE $1::uuid IS NULL OR d.id > $1
+               ORDER BY d.id
+               LIMIT $2"#
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Point a document at its relocated file. False when its path changed meanwhile.
+    pub async fn relocate_document_file(&self, document_id: Uuid, source_path: &str, target_path: &str) -> Result<bool> {
+        let updated = sqlx::query(
+            "UPDATE documents SET file_path = $3, updated_at = NOW() WHERE id = $1 AND file_path = $2"
+        )
+        .bind(document_id)
+        .bind(source_path)
+        .bind(target_path)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(updated > 0)
+    }
+
+    pub async fn update_storage_relocation_progress(
+        &self,
+        id: Uuid,
+        last_document_id: Uuid,
+        moved: i64,
+        skipped: i64,
+        failures: &[StorageMigrationFailure],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE storage_relocations
+               SET last_document_id = $2,
+                   moved_documents = moved_documents + $3,
+                   skipped_documents = skipped_documents + $4,
+                   failed_documents = failed_documents + $5,
+                   failures = CASE WHEN jsonb_array_length(failures) >= $7 THEN failures
+                                   ELSE failures || $6 END
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .bind(last_document_id)
+        .bind(moved)
+        .bind(skipped)
+        .bind(failures.len() as i64)
+        .bind(sqlx::types::Json(failures))
+        .bind(MAX_RECORDED_FAILURES)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn finish_storage_relocation(
+        &self,
+        id: Uuid,
+        status: StorageMigrationStatus,
+        error: Option<&str>,
+    ) -> Result<StorageRelocation> {
+        let query = format!(
+            r#"UPDATE storage_relocations
+               SET status = $2, error = $3, completed_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            STORAGE_RELOCATION_COLUMNS
+        );
+
+        let relocation = sqlx::query_as::<_, StorageRelocation>(&query)
+            .bind(id)
+            .bind(status.to_string())
+            .bind(error)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(relocation)
+    }
+}
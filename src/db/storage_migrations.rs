@@ -0,0 +1,278 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{StorageBackendKind, StorageMigration, StorageMigrationReport, StorageMigrationStatus};
+
+const STORAGE_MIGRATION_COLUMNS: &str = "id, target_backend, status, batch_size, delete_source, total_documents, \
+     migrated_documents, skipped_documents, failed_documents, last_document_id, report, error, created_by, \
+     created_at, completed_at";
+
+impl Database {
+    pub async fn create_storage_migration(
+        &self,
+        target_backend: StorageBackendKind,
+        batch_size: i32,
+        delete_source: bool,
+        created_by: Uuid,
+    ) -> Result<StorageMigration> {
+        let query = format!(
+            r#"INSERT INTO storage_migrations (target_backend, batch_size, delete_source, total_documents, created_by)
+               VALUES ($1, $2, $3, (SELECT COUNT(*) FROM documents), $4)
+               RETURNING {}"#,
+            STORAGE_MIGRATION_COLUMNS
+        );
+
+        let migration = sqlx::query_as::<_, StorageMigration>(&query)
+            .bind(target_backend.to_string())
+            .bind(batch_size)
+            .bind(delete_source)
+            .bind(created_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(migration)
+    }
+
+    pub async fn get_storage_migration(&self, id: Uuid) -> Result<Option<StorageMigration>> {
+        let query = format!("SELECT {} FROM storage_migrations WHERE id = $1", STORAGE_MIGRATION_COLUMNS);
+        let migration = sqlx::query_as::<_, StorageMigration>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(migration)
+    }
+
+    pub async fn list_storage_migrations(&self) -> Result<Vec<StorageMigration>> {
+        let query = format!(
+            "SELECT {} FROM storage_migrations ORDER BY created_at DESC",
+            STORAGE_MIGRATION_COLUMNS
+        );
+        let migrations = sqlx::query_as::<_, StorageMigration>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(migrations)
+    }
+
+    pub async fn get_running_storage_migration(&self) -> Result<Option<StorageMigration>> {
+        let query = format!(
+            "SELECT {} FROM storage_migrations WHERE status = 'running'",
+            STORAGE_MIGRATION_COLUMNS
+        );
+        let migration = sqlx::query_as::<_, StorageMigration>(&query)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(migration)
+    }
+
+    /// Next batch of documents in id order: id, user id, filename, file path and recorded hash
+    pub async fn get_documents_for_storage_migration(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<(Uuid, Uuid, String, String, Option<String>)>> {
+        let rows = sqlx::query_as::<_, (Uuid, Uuid, String, String, Option<String>)>(
+            r#"SELECT id, user_id, filename, file_path, file_hash
+               FROM documents
+               WHERE $1::uuid IS NULL OR id > $1
+               ORDER BY id
+               LIMIT $2"#
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Point the document at its verified copy and record the move. Returns false, without
+    /// recording anything, when the document's path changed while it was being copied.
+    pub async fn complete_storage_migration_item(
+        &self,
+        migration_id: Uuid,
+        document_id: Uuid,
+        source_path: &str,
+        target_path: &str,
+        sha256: &str,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query(
+            "UPDATE documents SET file_path = $3, updated_at = NOW() WHERE id = $1 AND file_path = $2"
+        )
+        .bind(document_id)
+        .bind(source_path)
+        .bind(target_path)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if updated == 0 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"INSERT INTO storage_migration_items (migration_id, document_id, source_path, target_path, sha256, status)
+               VALUES ($1, $2, $3, $4, $5, 'migrated')
+               ON CONFLICT (migration_id, document_id) DO UPDATE
+               SET source_path = EXCLUDED.source_path, target_path = EXCLUDED.target_path,
+                   sha256 = EXCLUDED.sha256, status = 'migrated', error = NULL, processed_at = NOW()"#
+        )
+        .bind(migration_id)
+        .bind(document_id)
+        .bind(source_path)
+        .bind(target_path)
+        .bind(sha256)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn fail_storage_migration_item(
+        &self,
+        migration_id: Uuid,
+        document_id: Uuid,
+        source_path: &str,
+        error: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO storage_migration_items (migration_id, document_id, source_path, status, error)
+               VALUES ($1, $2, $3, 'failed', $4)
+               ON CONFLICT (migration_id, document_id) DO UPDATE
+               SET source_path = EXCLUDED.source_path, status = 'failed', error = EXCLUDED.error, processed_at = NOW()"#
+        )
+        .bind(migration_id)
+        .bind(document_id)
+        .bind(source_path)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Advance the resume cursor after a batch and add the batch's counts
+    pub async fn update_storage_migration_progress(
+        &self,
+        id: Uuid,
+        last_document_id: Uuid,
+        migrated: i64,
+        skipped: i64,
+        failed: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE storage_migrations
+               SET last_document_id = $2,
+                   migrated_documents = migrated_documents + $3,
+                   skipped_documents = skipped_documents + $4,
+                   failed_documents = failed_documents + $5
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .bind(last_document_id)
+        .bind(migrated)
+        .bind(skipped)
+        .bind(failed)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Migrated items with the document's current path (None once the document is deleted):
+    /// document id, source path, target path, current path
+    pub async fn get_migrated_storage_items(
+        &self,
+        migration_id: Uuid,
+    ) -> Result<Vec<(Uuid, String, String, Option<String>)>> {
+        let rows = sqlx::query_as::<_, (Uuid, String, String, Option<String>)>(
+            r#"SELECT i.document_id, i.source_path, i.target_path, d.file_path
+               FROM storage_migration_items i
+               LEFT JOIN documents d ON d.id = i.document_id
+               WHERE i.migration_id = $1 AND i.status = 'migrated' AND NOT i.source_deleted"#
+        )
+        .bind(migration_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Failed items: document id, source path, error
+    pub async fn get_failed_storage_items(&self, migration_id: Uuid) -> Result<Vec<(Uuid, String, String)>> {
+        let rows = sqlx::query_as::<_, (Uuid, String, String)>(
+            r#"SELECT document_id, source_path, COALESCE(error, '')
+               FROM storage_migration_items
+               WHERE migration_id = $1 AND status = 'failed'
+               ORDER BY document_id"#
+        )
+        .bind(migration_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn mark_storage_migration_source_deleted(&self, migration_id: Uuid, document_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE storage_migration_items SET source_deleted = TRUE WHERE migration_id = $1 AND document_id = $2"
+        )
+        .bind(migration_id)
+        .bind(document_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Put a failed migration back into the running state so it continues from its cursor
+    pub async fn reopen_storage_migration(&self, id: Uuid) -> Result<StorageMigration> {
+        let query = format!(
+            r#"UPDATE storage_migrations
+               SET status = 'running', error = NULL, report = NULL, completed_at = NULL
+               WHERE id = $1
+               RETURNING {}"#,
+            STORAGE_MIGRATION_COLUMNS
+        );
+
+        let migration = sqlx::query_as::<_, StorageMigration>(&query)
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(migration)
+    }
+
+    pub async fn finish_storage_migration(
+        &self,
+        id: Uuid,
+        status: StorageMigrationStatus,
+        report: Option<&StorageMigrationReport>,
+        error: Option<&str>,
+    ) -> Result<StorageMigration> {
+        let query = format!(
+            r#"UPDATE storage_migrations
+               SET status = $2, report = $3, error = $4, completed_at = NOW()
+               WHERE id = $1
+               RETURNING {}"#,
+            STORAGE_MIGRATION_COLUMNS
+        );
+
+        let migration = sqlx::query_as::<_, StorageMigration>(&query)
+            .bind(id)
+            .bind(status.to_string())
+            .bind(report.map(sqlx::types::Json))
+            .bind(error)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(migration)
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+
+impl Database {
+    /// Total size and number of the user's documents
+    pub async fn get_user_storage_used(&self, user_id: Uuid) -> Result<(i64, i64)> {
+        let (used_bytes, document_count): (i64, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(file_size), 0)::BIGINT, COUNT(*) FROM documents WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((used_bytes, document_count))
+    }
+
+    /// The quota an admin set for the user: None without an override, Some(None) when
+    /// they are exempt from any limit
+    pub async fn get_user_storage_quota_override(&self, user_id: Uuid) -> Result<Option<Option<i64>>> {
+        let quota = sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT quota_bytes FROM user_storage_quotas WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(quota)
+    }
+
+    pub async fn set_user_storage_quota(&self, user_id: Uuid, quota_bytes: Option<i64>, set_by: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO user_storage_quotas (user_id, quota_bytes, set_by) VALUES ($1, $2, $3)
+               ON CONFLICT (user_id) DO UPDATE
+               SET quota_bytes = EXCLUDED.quota_bytes, set_by = EXCLUDED.set_by, updated_at = NOW()"#
+        )
+        .bind(user_id)
+        .bind(quota_bytes)
+        .bind(set_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns false when the user had no override
+    pub async fn delete_user_storage_quota(&self, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM user_storage_quotas WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
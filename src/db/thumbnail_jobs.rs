@@ -0,0 +1,134 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::ThumbnailQueueStats;
+
+/// Minutes after which a job still processing is assumed to belong to a worker that
+/// stopped, and is claimed again
+const STALE_PROCESSING_MINUTES: i32 = 10;
+
+impl Database {
+    /// Queue a document's thumbnail for generation, again if it was generated from
+    /// content that has since been replaced
+    pub async fn enqueue_thumbnail_job(&self, document_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO thumbnail_jobs (document_id) VALUES ($1)
+               ON CONFLICT (document_id) DO UPDATE
+               SET status = 'pending', attempts = 0, error = NULL, next_attempt_at = NOW(), updated_at = NOW()"#,
+        )
+        .bind(document_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Due jobs, oldest first, marked as processing so concurrent workers skip them.
+    /// Returns the document ids with the attempt each claim is.
+    pub async fn claim_thumbnail_jobs(&self, limit: i64) -> Result<Vec<(Uuid, i32)>> {
+        let jobs = sqlx::query_as::<_, (Uuid, i32)>(
+            r#"UPDATE thumbnail_jobs
+               SET status = 'processing', attempts = attempts + 1, updated_at = NOW()
+               WHERE document_id IN (
+                   SELECT document_id FROM thumbnail_jobs
+                   WHERE (status = 'pending' AND next_attempt_at <= NOW())
+                      OR (status = 'processing' AND updated_at < NOW() - make_interval(mins => $2))
+                   ORDER BY next_attempt_at
+                   LIMIT $1
+                   FOR UPDATE SKIP LOCKED
+               )
+               RETURNING document_id, attempts"#,
+        )
+        .bind(limit)
+        .bind(STALE_PROCESSING_MINUTES)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    pub async fn complete_thumbnail_job(&self, document_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE thumbnail_jobs SET status = 'completed', error = NULL, updated_at = NOW() WHERE document_id = $1",
+        )
+        .bind(document_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt: the job is retried after `retry_in_seconds`, or marked
+    /// failed when there is no retry left
+    pub async fn fail_thumbnail_job(&self, document_id: Uuid, error: &str, retry_in_seconds: Option<i64>) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE thumbnail_jobs
+               SET status = CASE WHEN $3::bigint IS NULL THEN 'failed' ELSE 'pending' END,
+                   error = $2,
+                   next_attempt_at = NOW() + make_interval(secs => COALESCE($3, 0)),
+                   updated_at = NOW()
+               WHERE document_id = $1"#,
+        )
+        .bind(document_id)
+        .bind(error)
+        .bind(retry_in_seconds)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Queue every document that was never queued, and those that failed, e.g. after
+    /// a converter they needed was installed. Returns how many were queued.
+    pub async fn backfill_thumbnail_jobs(&self) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+        let requeued = sqlx::query(
+            r#"UPDATE thumbnail_jobs
+               SET status = 'pending', attempts = 0, error = NULL, next_attempt_at = NOW(), updated_at = NOW()
+               WHERE status = 'failed'"#,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let queued = sqlx::query(
+            r#"INSERT INTO thumbnail_jobs (document_id)
+               SELECT d.id FROM documents d
+               WHERE NOT EXISTS (SELECT 1 FROM thumbnail_jobs j WHERE j.document_id = d.id)
+               ON CONFLICT (document_id) DO NOTHING"#,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        tx.commit().await?;
+
+        Ok((requeued + queued) as i64)
+    }
+
+    pub async fn get_thumbnail_queue_stats(&self) -> Result<ThumbnailQueueStats> {
+        let stats = sqlx::query_as::<_, ThumbnailQueueStats>(
+            r#"SELECT COUNT(*) FILTER (WHERE j.status = 'pending') AS pending,
+                      COUNT(*) FILTER (WHERE j.status = 'processing') AS processing,
+                      COUNT(*) FILTER (WHERE j.status = 'completed') AS completed,
+                      COUNT(*) FILTER (WHERE j.status = 'failed') AS failed,
+                      COUNT(*) FILTER (WHERE j.document_id IS NULL) AS not_queued
+               FROM documents d
+               LEFT JOIN thumbnail_jobs j ON j.document_id = d.id"#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(stats)
+    }
+
+    /// Whether documents are waiting for OCR, which goes before thumbnails
+    pub async fn has_pending_ocr_jobs(&self) -> Result<bool> {
+        let pending = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM ocr_queue WHERE status = 'pending' AND attempts < max_attempts)",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(pending)
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{GraphEntity, TimelineEvent};
+
+/// Events of the latest graph version of their document, aliased `t`, with the
+/// document aliased `d`
+const LATEST_EVENTS: &str = r#"
+    document_timeline_events t
+    JOIN document_graph_versions v ON v.id = t.version_id
+    JOIN documents d ON d.id = t.document_id
+    WHERE v.version_number = (SELECT MAX(version_number) FROM document_graph_versions WHERE document_id = v.document_id)
+"#;
+
+impl Database {
+    /// The document's events from its latest analysis, oldest first
+    pub async fn get_document_timeline(&self, document_id: Uuid) -> Result<Vec<TimelineEvent>> {
+        let query = format!(
+            r#"SELECT t.id, t.document_id, d.original_filename AS filename, t.event_date,
+                      t.date_precision, t.description, t.entities
+               FROM {}
+                 AND t.document_id = $1
+               ORDER BY t.event_date, t.created_at"#,
+            LATEST_EVENTS
+        );
+        let events = sqlx::query_as::<_, TimelineEvent>(&query)
+            .bind(document_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(events)
+    }
+
+    /// Events across the user's documents between the dates, oldest first; with an
+    /// entity, only those naming it
+    pub async fn list_timeline_events(
+        &self,
+        user_id: Uuid,
+        entity: Option<&GraphEntity>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        limit: i64,
+    ) -> Result<Vec<TimelineEvent>> {
+        let query = format!(
+            r#"SELECT t.id, t.document_id, d.original_filename AS filename, t.event_date,
+                      t.date_precision, t.description, t.entities
+               FROM {}
+                 AND d.user_id = $1
+                 AND ($2::text IS NULL OR EXISTS (SELECT 1 FROM UNNEST(t.entities) AS name
+                                                  WHERE lower(btrim(name)) = $2))
+                 AND ($3::date IS NULL OR t.event_date >= $3)
+                 AND ($4::date IS NULL OR t.event_date <= $4)
+               ORDER BY t.event_date, d.id, t.created_at
+               LIMIT $5"#,
+            LATEST_EVENTS
+        );
+        let events = sqlx::query_as::<_, TimelineEvent>(&query)
+            .bind(user_id)
+            .bind(entity.map(|entity| entity.normalized_name.as_str()))
+            .bind(from)
+            .bind(to)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(events)
+    }
+}
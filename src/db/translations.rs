@@ -0,0 +1,95 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{DocumentTranslation, DocumentTranslationSummary, TranslationProvider};
+
+const TRANSLATION_FIELDS: &str =
+    "id, document_id, language, text, provider, model, source_chars, created_by, created_at, updated_at";
+
+impl Database {
+    /// Stores a translation, replacing the document's earlier one in the same language
+    pub async fn save_document_translation(
+        &self,
+        document_id: Uuid,
+        language: &str,
+        text: &str,
+        provider: TranslationProvider,
+        model: Option<&str>,
+        source_chars: i32,
+        created_by: Uuid,
+    ) -> Result<DocumentTranslation> {
+        let query = format!(
+            r#"INSERT INTO document_translations (document_id, language, text, provider, model, source_chars, created_by)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)
+               ON CONFLICT (document_id, language) DO UPDATE
+               SET text = EXCLUDED.text, provider = EXCLUDED.provider, model = EXCLUDED.model,
+                   source_chars = EXCLUDED.source_chars, created_by = EXCLUDED.created_by, updated_at = NOW()
+               RETURNING {}"#,
+            TRANSLATION_FIELDS
+        );
+        let translation = sqlx::query_as::<_, DocumentTranslation>(&query)
+            .bind(document_id)
+            .bind(language)
+            .bind(text)
+            .bind(provider.to_string())
+            .bind(model)
+            .bind(source_chars)
+            .bind(created_by)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(translation)
+    }
+
+    pub async fn list_document_translations(&self, document_id: Uuid) -> Result<Vec<DocumentTranslationSummary>> {
+        let translations = sqlx::query_as::<_, DocumentTranslationSummary>(
+            r#"SELECT language, provider, model, source_chars, char_length(text) AS chars, updated_at
+               FROM document_translations WHERE document_id = $1 ORDER BY language"#
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(translations)
+    }
+
+    pub async fn get_document_translation(&self, document_id: Uuid, language: &str) -> Result<Option<DocumentTranslation>> {
+        let query = format!(
+            "SELECT {} FROM document_translations WHERE document_id = $1 AND language = $2",
+            TRANSLATION_FIELDS
+        );
+        let translation = sqlx::query_as::<_, DocumentTranslation>(&query)
+            .bind(document_id)
+            .bind(language)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(translation)
+    }
+
+    pub async fn delete_document_translation(&self, document_id: Uuid, language: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM document_translations WHERE document_id = $1 AND language = $2")
+            .bind(document_id)
+            .bind(language)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Recomputes the search vectors of translations, e.g. after a search analyzer
+    /// changed. Limited to translations indexed with `readur_<language>` when given.
+    pub async fn rebuild_translation_search_vectors(&self, language: Option<&str>) -> Result<u64> {
+        let result = sqlx::query(
+            r#"UPDATE document_translations
+               SET search_vector = readur_search_vector(text, readur_translation_config(language))
+               WHERE $1::text IS NULL OR readur_translation_config(language) = ('readur_' || $1)::regconfig"#
+        )
+        .bind(language)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
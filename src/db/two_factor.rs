@@ -0,0 +1,195 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{AuthPolicy, UserTwoFactor};
+
+/// Failed second-factor attempts before the account's second factor is locked
+const MAX_FAILED_ATTEMPTS: i32 = 5;
+const LOCKOUT_MINUTES: i32 = 15;
+
+impl Database {
+    pub async fn get_user_two_factor(&self, user_id: Uuid) -> Result<Option<UserTwoFactor>> {
+        let two_factor = sqlx::query_as::<_, UserTwoFactor>(
+            r#"SELECT user_id, secret, enabled_at, last_used_step, failed_attempts, locked_until, created_at
+               FROM user_two_factor WHERE user_id = $1"#
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(two_factor)
+    }
+
+    /// Starts enrollment with a new secret, replacing any enrollment not yet finished.
+    /// Returns false when two-factor login is already enabled.
+    pub async fn begin_two_factor_setup(&self, user_id: Uuid, secret: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"INSERT INTO user_two_factor (user_id, secret) VALUES ($1, $2)
+               ON CONFLICT (user_id) DO UPDATE
+               SET secret = EXCLUDED.secret, last_used_step = NULL, failed_attempts = 0,
+                   locked_until = NULL, created_at = NOW()
+               WHERE user_two_factor.enabled_at IS NULL"#
+        )
+        .bind(user_id)
+        .bind(secret)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Enables two-factor login and replaces the recovery codes
+    pub async fn enable_two_factor(&self, user_id: Uuid, step: i64, recovery_code_hashes: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"UPDATE user_two_factor
+               SET enabled_at = NOW(), last_used_step = $2, failed_attempts = 0, locked_until = NULL
+               WHERE user_id = $1"#
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&mut *tx)
+        .await?;
+
+        Self::replace_recovery_codes_in(&mut tx, user_id, recovery_code_hashes).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Removes the second factor and recovery codes; returns false when there was none
+    pub async fn disable_two_factor(&self, user_id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM user_two_factor WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn replace_recovery_codes(&self, user_id: Uuid, code_hashes: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::replace_recovery_codes_in(&mut tx, user_id, code_hashes).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn replace_recovery_codes_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+        code_hashes: &[String],
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO user_recovery_codes (user_id, code_hash) SELECT $1, UNNEST($2::text[])"
+        )
+        .bind(user_id)
+        .bind(code_hashes)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn count_unused_recovery_codes(&self, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Marks a recovery code used; false when it does not exist or was used before
+    pub async fn use_recovery_code(&self, user_id: Uuid, code_hash: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"UPDATE user_recovery_codes SET used_at = NOW()
+               WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"#
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records an accepted code: its step can no longer be used and failures reset.
+    /// Returns false when a concurrent request already used this or a later step.
+    pub async fn record_two_factor_success(&self, user_id: Uuid, step: Option<i64>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"UPDATE user_two_factor
+               SET last_used_step = COALESCE($2, last_used_step), failed_attempts = 0, locked_until = NULL
+               WHERE user_id = $1 AND ($2 IS NULL OR last_used_step IS NULL OR last_used_step < $2)"#
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Counts a wrong code, locking the second factor for a while after too many
+    pub async fn record_two_factor_failure(&self, user_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE user_two_factor
+               SET failed_attempts = failed_attempts + 1,
+                   locked_until = CASE WHEN failed_attempts + 1 >= $2
+                                       THEN NOW() + make_interval(mins => $3)
+                                       ELSE locked_until END
+               WHERE user_id = $1"#
+        )
+        .bind(user_id)
+        .bind(MAX_FAILED_ATTEMPTS)
+        .bind(LOCKOUT_MINUTES)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_auth_policy(&self) -> Result<AuthPolicy> {
+        let policy = sqlx::query_as::<_, AuthPolicy>(
+            "SELECT require_two_factor, updated_by, updated_at FROM auth_policy WHERE id"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(policy.unwrap_or(AuthPolicy {
+            require_two_factor: false,
+            updated_by: None,
+            updated_at: chrono::Utc::now(),
+        }))
+    }
+
+    pub async fn update_auth_policy(&self, require_two_factor: bool, updated_by: Uuid) -> Result<AuthPolicy> {
+        let policy = sqlx::query_as::<_, AuthPolicy>(
+            r#"INSERT INTO auth_policy (id, require_two_factor, updated_by, updated_at)
+               VALUES (TRUE, $1, $2, NOW())
+               ON CONFLICT (id) DO UPDATE
+               SET require_two_factor = EXCLUDED.require_two_factor,
+                   updated_by = EXCLUDED.updated_by, updated_at = NOW()
+               RETURNING require_two_factor, updated_by, updated_at"#
+        )
+        .bind(require_two_factor)
+        .bind(updated_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(policy)
+    }
+}
@@ -363,4 +363,35 @@ impl Database {
             auth_provider: row.get::<String, _>("auth_provider").try_into().unwrap_or(AuthProvider::Local),
         })
     }
-}
\ No newline at end of file
+
+    pub async fn update_user_role(&self, id: Uuid, role: crate::models::UserRole) -> Result<()> {
+        sqlx::query("UPDATE users SET role = $2, updated_at = NOW() WHERE id = $1")
+            .bind(id)
+            .bind(role.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The locale the user chose, if any
+    pub async fn get_user_locale(&self, id: Uuid) -> Result<Option<String>> {
+        let locale = sqlx::query_scalar::<_, Option<String>>("SELECT locale FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(locale.flatten())
+    }
+
+    /// None goes back to the server's default locale
+    pub async fn set_user_locale(&self, id: Uuid, locale: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE users SET locale = $2, updated_at = NOW() WHERE id = $1")
+            .bind(id)
+            .bind(locale)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
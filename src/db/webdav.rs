@@ -92,7 +92,8 @@ impl Database {
         Ok(result.rows_affected() as i64)
     }
 
-    // Reset any running source syncs on startup (handles server restart during sync)
+    // Reset any running source syncs on startup (handles server restart during sync).
+    // Syncs run by nodes still online are left to them.
     pub async fn reset_running_source_syncs(&self) -> Result<i64> {
         let result = sqlx::query(
             r#"UPDATE sources 
@@ -104,7 +105,8 @@ impl Database {
                    END,
                    last_error_at = NOW(),
                    updated_at = NOW()
-               WHERE status = 'syncing'"#
+               WHERE status = 'syncing'
+                 AND (sync_node_id IS NULL OR sync_node_id NOT IN (SELECT id FROM live_worker_nodes))"#
         )
         .execute(&self.pool)
         .await?;
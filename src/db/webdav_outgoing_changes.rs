@@ -0,0 +1,78 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::WebDAVOutgoingChange;
+
+const OUTGOING_CHANGE_FIELDS: &str = "id, source_id, document_id, operation, remote_path, new_remote_path, expected_etag, status, attempts, last_error, created_at, updated_at";
+
+impl Database {
+    pub async fn queue_webdav_outgoing_change(
+        &self,
+        source_id: Uuid,
+        document_id: Uuid,
+        operation: &str,
+        remote_path: &str,
+        new_remote_path: Option<&str>,
+        expected_etag: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO webdav_outgoing_changes
+               (source_id, document_id, operation, remote_path, new_remote_path, expected_etag)
+               VALUES ($1, $2, $3, $4, $5, $6)"#
+        )
+        .bind(source_id)
+        .bind(document_id)
+        .bind(operation)
+        .bind(remote_path)
+        .bind(new_remote_path)
+        .bind(expected_etag)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Pending changes of a source in the order they were made
+    pub async fn get_pending_webdav_outgoing_changes(&self, source_id: Uuid) -> Result<Vec<WebDAVOutgoingChange>> {
+        let changes = sqlx::query_as::<_, WebDAVOutgoingChange>(&format!(
+            "SELECT {} FROM webdav_outgoing_changes WHERE source_id = $1 AND status = 'pending' ORDER BY created_at, id",
+            OUTGOING_CHANGE_FIELDS
+        ))
+        .bind(source_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(changes)
+    }
+
+    /// Most recent changes of a source, newest first
+    pub async fn list_webdav_outgoing_changes(&self, source_id: Uuid, limit: i64) -> Result<Vec<WebDAVOutgoingChange>> {
+        let changes = sqlx::query_as::<_, WebDAVOutgoingChange>(&format!(
+            "SELECT {} FROM webdav_outgoing_changes WHERE source_id = $1 ORDER BY created_at DESC, id LIMIT $2",
+            OUTGOING_CHANGE_FIELDS
+        ))
+        .bind(source_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(changes)
+    }
+
+    /// Record an attempt to apply a change; `status` stays `pending` for a retry
+    pub async fn update_webdav_outgoing_change(&self, change_id: Uuid, status: &str, last_error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE webdav_outgoing_changes
+               SET status = $2, last_error = $3, attempts = attempts + 1, updated_at = NOW()
+               WHERE id = $1"#
+        )
+        .bind(change_id)
+        .bind(status)
+        .bind(last_error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{
+    CreateWebhookEndpointRequest, EventEnvelope, UpdateWebhookEndpointRequest, WebhookDelivery,
+    WebhookDeliveryStatus, WebhookEndpoint, WebhookEventContext,
+};
+
+const WEBHOOK_ENDPOINT_COLUMNS: &str =
+    "id, user_id, url, description, enabled, filter, last_delivery_at, last_error, secret, created_at, updated_at";
+
+const WEBHOOK_DELIVERY_COLUMNS: &str = "id, endpoint_id, event_id, event_type, status, attempts, next_attempt_at, \
+    response_status, last_error, created_at, last_attempt_at";
+
+/// How long a claimed delivery stays hidden from other workers while it is attempted
+const DELIVERY_CLAIM_SECONDS: i32 = 300;
+
+impl Database {
+    pub async fn create_webhook_endpoint(
+        &self,
+        user_id: Uuid,
+        request: &CreateWebhookEndpointRequest,
+        secret: &str,
+    ) -> Result<WebhookEndpoint> {
+        let query = format!(
+            r#"INSERT INTO webhook_endpoints (user_id, url, description, enabled, filter, secret)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               RETURNING {}"#,
+            WEBHOOK_ENDPOINT_COLUMNS
+        );
+
+        let endpoint = sqlx::query_as::<_, WebhookEndpoint>(&query)
+            .bind(user_id)
+            .bind(&request.url)
+            .bind(&request.description)
+            .bind(request.enabled.unwrap_or(true))
+            .bind(sqlx::types::Json(&request.filter))
+            .bind(secret)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(endpoint)
+    }
+
+    pub async fn get_webhook_endpoints(&self, user_id: Uuid) -> Result<Vec<WebhookEndpoint>> {
+        let query = format!(
+            "SELECT {} FROM webhook_endpoints WHERE user_id = $1 ORDER BY created_at",
+            WEBHOOK_ENDPOINT_COLUMNS
+        );
+        let endpoints = sqlx::query_as::<_, WebhookEndpoint>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(endpoints)
+    }
+
+    pub async fn get_enabled_webhook_endpoints(&self, user_id: Uuid) -> Result<Vec<WebhookEndpoint>> {
+        let query = format!(
+            "SELECT {} FROM webhook_endpoints WHERE user_id = $1 AND enabled",
+            WEBHOOK_ENDPOINT_COLUMNS
+        );
+        let endpoints = sqlx::query_as::<_, WebhookEndpoint>(&query)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(endpoints)
+    }
+
+    pub async fn get_webhook_endpoint(&self, id: Uuid, user_id: Uuid) -> Result<Option<WebhookEndpoint>> {
+        let query = format!(
+            "SELECT {} FROM webhook_endpoints WHERE id = $1 AND user_id = $2",
+            WEBHOOK_ENDPOINT_COLUMNS
+        );
+        let endpoint = sqlx::query_as::<_, WebhookEndpoint>(&query)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(endpoint)
+    }
+
+    pub async fn update_webhook_endpoint(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        request: &UpdateWebhookEndpointRequest,
+    ) -> Result<Option<WebhookEndpoint>> {
+        let query = format!(
+            r#"UPDATE webhook_endpoints
+               SET url = COALESCE($3, url),
+                   description = COALESCE($4, description),
+                   enabled = COALESCE($5, enabled),
+                   filter = COALESCE($6, filter),
+                   updated_at = NOW()
+               WHERE id = $1 AND user_id = $2
+               RETURNING {}"#,
+            WEBHOOK_ENDPOINT_COLUMNS
+        );
+
+        let endpoint = sqlx::query_as::<_, WebhookEndpoint>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(&request.url)
+            .bind(&request.description)
+            .bind(request.enabled)
+            .bind(request.filter.as_ref().map(sqlx::types::Json))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(endpoint)
+    }
+
+    pub async fn delete_webhook_endpoint(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM webhook_endpoints WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_webhook_endpoint_secret(&self, id: Uuid, user_id: Uuid, secret: &str) -> Result<Option<WebhookEndpoint>> {
+        let query = format!(
+            "UPDATE webhook_endpoints SET secret = $3, updated_at = NOW() WHERE id = $1 AND user_id = $2 RETURNING {}",
+            WEBHOOK_ENDPOINT_COLUMNS
+        );
+        let endpoint = sqlx::query_as::<_, WebhookEndpoint>(&query)
+            .bind(id)
+            .bind(user_id)
+            .bind(secret)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(endpoint)
+    }
+
+    /// Record the outcome of a delivery; `error` is None on success
+    pub async fn record_webhook_delivery(&self, id: Uuid, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "UPDATE webhook_endpoints SET last_delivery_at = NOW(), last_error = $2 WHERE id = $1"
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Tags, label names, source and MIME type of a document, for webhook filters
+    pub async fn get_webhook_document_context(&self, document_id: Uuid) -> Result<Option<WebhookEventContext>> {
+        let row = sqlx::query(
+            r#"SELECT d.tags, d.source_id, d.mime_type,
+                      ARRAY(SELECT l.name::text FROM document_labels dl
+                            JOIN labels l ON l.id = dl.label_id
+                            WHERE dl.document_id = d.id) AS label_names
+               FROM documents d
+               WHERE d.id = $1"#
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let mut tags: Vec<String> = row.get::<Option<Vec<String>>, _>("tags").unwrap_or_default();
+            tags.extend(row.get::<Vec<String>, _>("label_names"));
+            WebhookEventContext {
+                tags,
+                source_id: row.get("source_id"),
+                mime_type: Some(row.get("mime_type")),
+            }
+        }))
+    }
+
+    /// Log a delivery of an event to an endpoint, due right away. Returns None when
+    /// the event was already delivered to the endpoint.
+    pub async fn create_webhook_delivery(&self, endpoint_id: Uuid, envelope: &EventEnvelope) -> Result<Option<WebhookDelivery>> {
+        let query = format!(
+            r#"INSERT INTO webhook_deliveries (endpoint_id, event_id, event_type, next_attempt_at)
+               VALUES ($1, $2, $3, NOW())
+               ON CONFLICT (endpoint_id, event_id) DO NOTHING
+               RETURNING {}"#,
+            WEBHOOK_DELIVERY_COLUMNS
+        );
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(&query)
+            .bind(endpoint_id)
+            .bind(envelope.event_id)
+            .bind(envelope.event_type.as_str())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(delivery)
+    }
+
+    /// Record an attempt. A pending delivery is retried at `next_attempt_at`.
+    pub async fn record_webhook_delivery_attempt(
+        &self,
+        id: Uuid,
+        status: WebhookDeliveryStatus,
+        response_status: Option<i32>,
+        error: Option<&str>,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE webhook_deliveries
+               SET status = $2, attempts = attempts + 1, response_status = $3, last_error = $4,
+                   next_attempt_at = $5, last_attempt_at = NOW()
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .bind(status.to_string())
+        .bind(response_status)
+        .bind(error)
+        .bind(next_attempt_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Pending deliveries whose retry is due, oldest first. They are pushed back while
+    /// they are attempted, so concurrent workers do not send them twice.
+    pub async fn claim_due_webhook_deliveries(&self, limit: i64) -> Result<Vec<WebhookDelivery>> {
+        let query = format!(
+            r#"UPDATE webhook_deliveries
+               SET next_attempt_at = NOW() + make_interval(secs => $2)
+               WHERE id IN (
+                   SELECT id FROM webhook_deliveries
+                   WHERE status = 'pending' AND next_attempt_at <= NOW()
+                   ORDER BY next_attempt_at
+                   LIMIT $1
+                   FOR UPDATE SKIP LOCKED
+               )
+               RETURNING {}"#,
+            WEBHOOK_DELIVERY_COLUMNS
+        );
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(&query)
+            .bind(limit)
+            .bind(DELIVERY_CLAIM_SECONDS)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(deliveries)
+    }
+
+    /// An endpoint's deliveries, newest first
+    pub async fn get_webhook_deliveries(
+        &self,
+        endpoint_id: Uuid,
+        status: Option<WebhookDeliveryStatus>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        let query = format!(
+            r#"SELECT {} FROM webhook_deliveries
+               WHERE endpoint_id = $1 AND ($2::text IS NULL OR status = $2)
+               ORDER BY created_at DESC
+               LIMIT $3 OFFSET $4"#,
+            WEBHOOK_DELIVERY_COLUMNS
+        );
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(&query)
+            .bind(endpoint_id)
+            .bind(status.map(|status| status.to_string()))
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Queue a delivery to be sent again right away with a fresh set of attempts
+    pub async fn retry_webhook_delivery(&self, id: Uuid, endpoint_id: Uuid) -> Result<Option<WebhookDelivery>> {
+        let query = format!(
+            r#"UPDATE webhook_deliveries
+               SET status = 'pending', attempts = 0, next_attempt_at = NOW()
+               WHERE id = $1 AND endpoint_id = $2
+               RETURNING {}"#,
+            WEBHOOK_DELIVERY_COLUMNS
+        );
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(&query)
+            .bind(id)
+            .bind(endpoint_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(delivery)
+    }
+
+    /// An endpoint regardless of its owner, for retrying deliveries
+    pub async fn get_webhook_endpoint_by_id(&self, id: Uuid) -> Result<Option<WebhookEndpoint>> {
+        let query = format!("SELECT {} FROM webhook_endpoints WHERE id = $1", WEBHOOK_ENDPOINT_COLUMNS);
+        let endpoint = sqlx::query_as::<_, WebhookEndpoint>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(endpoint)
+    }
+}
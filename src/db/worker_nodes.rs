@@ -0,0 +1,143 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::WorkerNode;
+
+/// Days a node that stopped or went silent is still listed
+const FORGET_NODES_AFTER_DAYS: i32 = 7;
+
+const WORKER_NODE_COLUMNS: &str = "n.*, EXISTS (SELECT 1 FROM live_worker_nodes l WHERE l.id = n.id) AS online";
+
+/// What a node reports with each heartbeat
+pub struct NodeStatus<'a> {
+    pub ocr_worker_id: Option<&'a str>,
+    pub active_ocr_jobs: i32,
+    pub max_ocr_jobs: i32,
+    pub exclusive_tasks: &'a [String],
+}
+
+impl Database {
+    /// Record a node that just started. Earlier runs under the same name on the same
+    /// host are marked stopped.
+    pub async fn register_worker_node(
+        &self,
+        id: Uuid,
+        name: &str,
+        hostname: &str,
+        roles: &[String],
+        version: &str,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"UPDATE worker_nodes SET stopped_at = NOW(), exclusive_tasks = '{}'
+               WHERE name = $1 AND hostname = $2 AND stopped_at IS NULL"#,
+        )
+        .bind(name)
+        .bind(hostname)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"INSERT INTO worker_nodes (id, name, hostname, roles, version) VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (id) DO UPDATE SET last_heartbeat_at = NOW(), stopped_at = NULL"#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(hostname)
+        .bind(roles)
+        .bind(version)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Record that the node is alive, with what it is doing. A node marked stopped
+    /// stays stopped.
+    pub async fn heartbeat_worker_node(&self, id: Uuid, status: &NodeStatus<'_>) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE worker_nodes
+               SET last_heartbeat_at = NOW(), ocr_worker_id = $2,
+                   active_ocr_jobs = $3, max_ocr_jobs = $4, exclusive_tasks = $5
+               WHERE id = $1 AND stopped_at IS NULL"#,
+        )
+        .bind(id)
+        .bind(status.ocr_worker_id)
+        .bind(status.active_ocr_jobs)
+        .bind(status.max_ocr_jobs)
+        .bind(status.exclusive_tasks)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record that the node shut down, so what it left behind is taken over at once
+    pub async fn mark_worker_node_stopped(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE worker_nodes SET stopped_at = NOW(), active_ocr_jobs = 0, exclusive_tasks = '{}' WHERE id = $1",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Nodes online first, then by when they last reported
+    pub async fn get_worker_nodes(&self) -> Result<Vec<WorkerNode>> {
+        let query = format!(
+            "SELECT {} FROM worker_nodes n ORDER BY online DESC, n.name, n.last_heartbeat_at DESC",
+            WORKER_NODE_COLUMNS
+        );
+        let nodes = sqlx::query_as::<_, WorkerNode>(&query).fetch_all(&self.pool).await?;
+
+        Ok(nodes)
+    }
+
+    pub async fn get_worker_node(&self, id: Uuid) -> Result<Option<WorkerNode>> {
+        let query = format!("SELECT {} FROM worker_nodes n WHERE n.id = $1", WORKER_NODE_COLUMNS);
+        let node = sqlx::query_as::<_, WorkerNode>(&query).bind(id).fetch_optional(&self.pool).await?;
+
+        Ok(node)
+    }
+
+    /// Remove a node that is no longer online from the list. False when it does not
+    /// exist or is still online.
+    pub async fn delete_offline_worker_node(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM worker_nodes WHERE id = $1 AND id NOT IN (SELECT id FROM live_worker_nodes)",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Drop nodes that stopped or went silent a while ago
+    pub async fn prune_worker_nodes(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM worker_nodes WHERE last_heartbeat_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(FORGET_NODES_AFTER_DAYS)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Whether another node with the role is online
+    pub async fn other_live_node_has_role(&self, node_id: Uuid, role: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM live_worker_nodes WHERE id <> $1 AND $2 = ANY(roles))",
+        )
+        .bind(node_id)
+        .bind(role)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+}
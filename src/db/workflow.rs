@@ -0,0 +1,113 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, QueryBuilder, Row};
+use uuid::Uuid;
+
+use super::documents::{apply_pagination, apply_shared_access_filter, map_row_to_document, DOCUMENT_FIELDS};
+use super::Database;
+use crate::models::{Document, SharePermission, UserRole, WorkflowCounts, WorkflowState};
+
+impl Database {
+    /// Number of documents the user can see in each workflow state
+    pub async fn get_workflow_counts(&self, user_id: Uuid, user_role: UserRole) -> Result<WorkflowCounts> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT workflow_state, COUNT(*) AS count FROM documents WHERE 1=1");
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::View);
+        query.push(" GROUP BY workflow_state");
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        let mut counts = WorkflowCounts::default();
+        for row in rows {
+            counts.add(row.get("workflow_state"), row.get("count"));
+        }
+        Ok(counts)
+    }
+
+    /// Documents the user can see in a workflow state, oldest first so an inbox is
+    /// worked through in the order documents arrived
+    pub async fn get_workflow_documents(
+        &self,
+        user_id: Uuid,
+        user_role: UserRole,
+        state: WorkflowState,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<(Document, WorkflowState, Option<DateTime<Utc>>)>> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT ");
+        query.push(DOCUMENT_FIELDS);
+        query.push(", workflow_state, workflow_state_changed_at FROM documents WHERE workflow_state = ");
+        query.push_bind(state);
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::View);
+        query.push(" ORDER BY created_at, id");
+        apply_pagination(&mut query, limit, offset);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(|row| (map_row_to_document(row), row.get("workflow_state"), row.get("workflow_state_changed_at")))
+            .collect())
+    }
+
+    /// Workflow states of those of the documents the user may edit
+    pub async fn get_workflow_states(
+        &self,
+        document_ids: &[Uuid],
+        user_id: Uuid,
+        user_role: UserRole,
+    ) -> Result<Vec<(Uuid, WorkflowState)>> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT id, workflow_state FROM documents WHERE id = ANY(");
+        query.push_bind(document_ids.to_vec());
+        query.push(")");
+        apply_shared_access_filter(&mut query, user_id, user_role, SharePermission::Edit);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows.iter().map(|row| (row.get("id"), row.get("workflow_state"))).collect())
+    }
+
+    /// Moves the documents to `to`, skipping any whose state changed since it was
+    /// checked to one `to` cannot be reached from. Returns the documents moved.
+    pub async fn transition_documents(&self, document_ids: &[Uuid], to: WorkflowState, changed_by: Uuid) -> Result<Vec<Uuid>> {
+        let moved = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE documents
+            SET workflow_state = $1, workflow_state_changed_at = NOW(), workflow_state_changed_by = $2, updated_at = NOW()
+            WHERE id = ANY($3) AND workflow_state::text = ANY($4)
+            RETURNING id
+            "#,
+        )
+        .bind(to)
+        .bind(changed_by)
+        .bind(document_ids)
+        .bind(WorkflowState::sources_of(to).into_iter().map(WorkflowState::as_str).collect::<Vec<_>>())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(moved)
+    }
+
+    pub async fn get_require_review_before_search(&self, user_id: Uuid) -> Result<bool> {
+        let required = sqlx::query_scalar::<_, bool>("SELECT require_review_before_search FROM settings WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(required.unwrap_or(false))
+    }
+
+    pub async fn set_require_review_before_search(&self, user_id: Uuid, required: bool) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO settings (user_id, require_review_before_search)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET
+                require_review_before_search = $2,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(required)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
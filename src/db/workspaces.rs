@@ -0,0 +1,244 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::Database;
+use crate::models::{Workspace, WorkspaceMember, WorkspaceMembership, WorkspaceRole, WorkspaceSettings};
+
+const WORKSPACE_SELECT: &str = r#"
+    SELECT w.id, w.name, w.slug, w.settings, w.created_by, w.created_at, w.updated_at,
+           (SELECT COUNT(*) FROM workspace_members m WHERE m.workspace_id = w.id) AS member_count
+    FROM workspaces w
+"#;
+
+/// The workspace tables that rows of a user can be moved into
+#[derive(Debug, Clone, Copy)]
+pub enum WorkspaceScoped {
+    Document,
+    Label,
+    Source,
+}
+
+impl WorkspaceScoped {
+    pub(crate) fn table(self) -> &'static str {
+        match self {
+            WorkspaceScoped::Document => "documents",
+            WorkspaceScoped::Label => "labels",
+            WorkspaceScoped::Source => "sources",
+        }
+    }
+}
+
+impl Database {
+    /// Creates the workspace with its creator as its first admin
+    pub async fn create_workspace(
+        &self,
+        name: &str,
+        slug: &str,
+        settings: &WorkspaceSettings,
+        created_by: Uuid,
+    ) -> Result<Workspace> {
+        let mut tx = self.pool.begin().await?;
+
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO workspaces (name, slug, settings, created_by) VALUES ($1, $2, $3, $4) RETURNING id"
+        )
+        .bind(name.trim())
+        .bind(slug)
+        .bind(sqlx::types::Json(settings))
+        .bind(created_by)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO workspace_members (workspace_id, user_id, role) VALUES ($1, $2, 'admin')")
+            .bind(id)
+            .bind(created_by)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        self.get_workspace(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Workspace {} disappeared after creation", id))
+    }
+
+    pub async fn get_workspaces(&self) -> Result<Vec<Workspace>> {
+        let query = format!("{} ORDER BY LOWER(w.name)", WORKSPACE_SELECT);
+        let workspaces = sqlx::query_as::<_, Workspace>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(workspaces)
+    }
+
+    pub async fn get_workspace(&self, id: Uuid) -> Result<Option<Workspace>> {
+        let query = format!("{} WHERE w.id = $1", WORKSPACE_SELECT);
+        let workspace = sqlx::query_as::<_, Workspace>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(workspace)
+    }
+
+    pub async fn workspace_slug_taken(&self, slug: &str) -> Result<bool> {
+        let taken: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM workspaces WHERE slug = $1)")
+            .bind(slug)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(taken)
+    }
+
+    /// Workspaces the user is a member of, with their role in each
+    pub async fn get_workspaces_for_user(&self, user_id: Uuid) -> Result<Vec<WorkspaceMembership>> {
+        let workspaces = sqlx::query_as::<_, WorkspaceMembership>(
+            r#"SELECT w.id, w.name, w.slug, m.role
+               FROM workspace_members m
+               JOIN workspaces w ON w.id = m.workspace_id
+               WHERE m.user_id = $1
+               ORDER BY LOWER(w.name)"#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(workspaces)
+    }
+
+    /// The user's role in the workspace; None when they are not a member
+    pub async fn get_workspace_role(&self, workspace_id: Uuid, user_id: Uuid) -> Result<Option<WorkspaceRole>> {
+        let role = sqlx::query_scalar::<_, WorkspaceRole>(
+            "SELECT role FROM workspace_members WHERE workspace_id = $1 AND user_id = $2"
+        )
+        .bind(workspace_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(role)
+    }
+
+    pub async fn update_workspace(
+        &self,
+        id: Uuid,
+        name: Option<&str>,
+        settings: Option<&WorkspaceSettings>,
+    ) -> Result<Option<Workspace>> {
+        let result = sqlx::query(
+            r#"UPDATE workspaces
+               SET name = COALESCE($2, name),
+                   settings = COALESCE($3, settings),
+                   updated_at = NOW()
+               WHERE id = $1"#
+        )
+        .bind(id)
+        .bind(name.map(str::trim))
+        .bind(settings.map(sqlx::types::Json))
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_workspace(id).await
+    }
+
+    /// Deletes a workspace along with every document, label and source in it
+    pub async fn delete_workspace(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM workspaces WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_workspace_members(&self, workspace_id: Uuid) -> Result<Vec<WorkspaceMember>> {
+        let members = sqlx::query_as::<_, WorkspaceMember>(
+            r#"SELECT m.user_id, u.username, m.role, m.added_at
+               FROM workspace_members m
+               JOIN users u ON u.id = m.user_id
+               WHERE m.workspace_id = $1
+               ORDER BY LOWER(u.username)"#
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(members)
+    }
+
+    /// Adds the user, or changes their role when they already are a member
+    pub async fn set_workspace_member(&self, workspace_id: Uuid, user_id: Uuid, role: WorkspaceRole) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO workspace_members (workspace_id, user_id, role) VALUES ($1, $2, $3)
+               ON CONFLICT (workspace_id, user_id) DO UPDATE SET role = EXCLUDED.role"#
+        )
+        .bind(workspace_id)
+        .bind(user_id)
+        .bind(role)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_workspace_member(&self, workspace_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM workspace_members WHERE workspace_id = $1 AND user_id = $2")
+            .bind(workspace_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn count_workspace_admins(&self, workspace_id: Uuid) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM workspace_members WHERE workspace_id = $1 AND role = 'admin'"
+        )
+        .bind(workspace_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Places a newly created row in a workspace
+    pub async fn assign_to_workspace(&self, scoped: WorkspaceScoped, id: Uuid, workspace_id: Uuid) -> Result<()> {
+        let query = format!("UPDATE {} SET workspace_id = $2 WHERE id = $1", scoped.table());
+        sqlx::query(&query)
+            .bind(id)
+            .bind(workspace_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The workspace a row is in; None for personal rows and rows that do not exist
+    pub async fn get_workspace_of(&self, scoped: WorkspaceScoped, id: Uuid) -> Result<Option<Uuid>> {
+        let query = format!("SELECT workspace_id FROM {} WHERE id = $1", scoped.table());
+        let workspace_id = sqlx::query_scalar::<_, Option<Uuid>>(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(workspace_id.flatten())
+    }
+
+    /// Settings of the workspace a document belongs to; None for personal documents
+    pub async fn get_document_workspace_settings(&self, document_id: Uuid) -> Result<Option<WorkspaceSettings>> {
+        let settings = sqlx::query_scalar::<_, sqlx::types::Json<WorkspaceSettings>>(
+            r#"SELECT w.settings FROM documents d
+               JOIN workspaces w ON w.id = d.workspace_id
+               WHERE d.id = $1"#
+        )
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(settings.map(|settings| settings.0))
+    }
+}
@@ -0,0 +1,671 @@
+//! Metadata embedded in files by the cameras and programs that made them: EXIF in
+//! photos, the info dictionary of PDFs and XMP packets in both. It is read into the
+//! document's searchable metadata, and identifying parts of it are stripped from
+//! copies according to `STRIP_EMBEDDED_METADATA`.
+
+use exif::{In, Tag};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+use std::ops::Range;
+
+/// Info dictionary entries read from PDFs, with the metadata key they are stored under
+const PDF_INFO_KEYS: &[(&str, &str)] = &[
+    ("Title", "title"),
+    ("Author", "author"),
+    ("Subject", "subject"),
+    ("Keywords", "keywords"),
+    ("Creator", "creator_tool"),
+    ("Producer", "producer"),
+];
+
+/// Info dictionary entries that name people or their software, blanked when stripping
+const PDF_IDENTIFYING_KEYS: &[&str] = &["Author", "Creator", "Producer"];
+
+/// XMP properties read, with the metadata key they are stored under
+const XMP_PROPERTIES: &[(&str, &str)] = &[
+    ("dc:title", "title"),
+    ("dc:creator", "author"),
+    ("dc:description", "subject"),
+    ("pdf:Keywords", "keywords"),
+    ("xmp:CreatorTool", "creator_tool"),
+    ("pdf:Producer", "producer"),
+];
+
+/// EXIF text tags read, with the metadata key they are stored under
+const EXIF_TEXT_TAGS: &[(Tag, &str)] = &[
+    (Tag::Make, "camera_make"),
+    (Tag::Model, "camera_model"),
+    (Tag::LensModel, "lens_model"),
+    (Tag::Software, "software"),
+    (Tag::Artist, "artist"),
+    (Tag::Copyright, "copyright"),
+];
+
+/// PNG chunks holding EXIF, text and XMP, or the time the image was last changed
+const PNG_METADATA_CHUNKS: &[&[u8]] = &[b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Smallest valid XMP packet, which a stripped packet in a PDF is replaced with
+const EMPTY_XMP_PACKET: &[u8] = br#"<x:xmpmeta xmlns:x="adobe:ns:meta/"/>"#;
+
+/// Which copies of files have their identifying metadata stripped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StripPolicy {
+    /// Files are stored and served as they were received
+    Never,
+    /// Copies served through share links or to users a document is shared with
+    Shared,
+    /// Files are stripped before they are stored, and older files when shared
+    Always,
+}
+
+impl StripPolicy {
+    /// `never` (also when unset), `shared` or `always`
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            None | Some("") | Some("never") | Some("none") => Ok(StripPolicy::Never),
+            Some("shared") => Ok(StripPolicy::Shared),
+            Some("always") | Some("all") => Ok(StripPolicy::Always),
+            Some(other) => Err(format!(
+                "STRIP_EMBEDDED_METADATA must be never, shared or always, not '{}'",
+                other
+            )),
+        }
+    }
+
+    /// Whether files are stripped before they are stored
+    pub fn strips_stored(self) -> bool {
+        self == StripPolicy::Always
+    }
+
+    /// Whether copies leaving through share links or sharing are stripped
+    pub fn strips_shared(self) -> bool {
+        self != StripPolicy::Never
+    }
+}
+
+static STRIP_POLICY: Lazy<Result<StripPolicy, String>> =
+    Lazy::new(|| StripPolicy::parse(std::env::var("STRIP_EMBEDDED_METADATA").ok().as_deref()));
+
+/// The policy configured in the environment, an error when `STRIP_EMBEDDED_METADATA`
+/// has an unknown value
+pub fn configured() -> Result<StripPolicy, String> {
+    STRIP_POLICY.clone()
+}
+
+/// Embedded metadata of a file as flat, searchable document metadata: the camera,
+/// capture time and position of photos, and the author and programs of documents
+pub fn read(data: &[u8], mime_type: &str) -> Map<String, Value> {
+    let mut metadata = Map::new();
+    if mime_type == "application/pdf" {
+        read_pdf_info(data, &mut metadata);
+    } else if mime_type.starts_with("image/") {
+        read_exif(data, &mut metadata);
+    } else {
+        return metadata;
+    }
+    if let Some(packet) = xmp_packet_ranges(data).pop() {
+        read_xmp(&String::from_utf8_lossy(&data[packet]), &mut metadata);
+    }
+    metadata
+}
+
+/// Whether `strip` can remove metadata from files of this type
+pub fn can_strip(mime_type: &str) -> bool {
+    matches!(mime_type, "application/pdf" | "image/jpeg" | "image/png")
+}
+
+/// A copy of the file without identifying metadata, or None when files of this type
+/// cannot be stripped or the file is malformed. JPEGs and PNGs lose their EXIF, XMP,
+/// IPTC and comments, including the EXIF orientation; PDFs keep their size and
+/// layout, with the author and producing programs of their info dictionary and their
+/// XMP packets blanked where they are not compressed.
+pub fn strip(data: &[u8], mime_type: &str) -> Option<Vec<u8>> {
+    match mime_type {
+        "application/pdf" => Some(strip_pdf(data)),
+        "image/jpeg" => strip_jpeg(data),
+        "image/png" => strip_png(data),
+        _ => None,
+    }
+}
+
+fn read_exif(data: &[u8], metadata: &mut Map<String, Value>) {
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut std::io::Cursor::new(data)) else {
+        return;
+    };
+
+    for (tag, key) in EXIF_TEXT_TAGS {
+        if let Some(text) = exif_text(&exif, *tag) {
+            metadata.insert(key.to_string(), Value::String(text));
+        }
+    }
+    let taken_at = [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+        .into_iter()
+        .find_map(|tag| exif_datetime(&exif, tag));
+    if let Some(taken_at) = taken_at {
+        metadata.insert("taken_at".to_string(), Value::String(taken_at));
+    }
+
+    let latitude = gps_coordinate(&exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, 'S');
+    let longitude = gps_coordinate(&exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, 'W');
+    if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+        if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) {
+            metadata.insert("gps_latitude".to_string(), Value::from(round_coordinate(latitude)));
+            metadata.insert("gps_longitude".to_string(), Value::from(round_coordinate(longitude)));
+            if let Some(altitude) = gps_altitude(&exif) {
+                metadata.insert("gps_altitude".to_string(), Value::from((altitude * 10.0).round() / 10.0));
+            }
+        }
+    }
+}
+
+/// First non-empty string of an ASCII tag
+fn exif_text(exif: &exif::Exif, tag: Tag) -> Option<String> {
+    let exif::Value::Ascii(ref strings) = exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    strings
+        .iter()
+        .map(|s| String::from_utf8_lossy(s).trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string())
+        .find(|s| !s.is_empty())
+}
+
+/// A date tag as `YYYY-MM-DDTHH:MM:SS`, in the camera's local time. Cameras without
+/// a clock write zeros, which count as no date.
+fn exif_datetime(exif: &exif::Exif, tag: Tag) -> Option<String> {
+    let exif::Value::Ascii(ref strings) = exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let datetime = exif::DateTime::from_ascii(strings.first()?).ok()?;
+    if datetime.year == 0 || datetime.month == 0 || datetime.day == 0 {
+        return None;
+    }
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        datetime.year, datetime.month, datetime.day, datetime.hour, datetime.minute, datetime.second
+    ))
+}
+
+/// Degrees, minutes and seconds as signed decimal degrees
+fn gps_coordinate(exif: &exif::Exif, tag: Tag, reference_tag: Tag, negative: char) -> Option<f64> {
+    let exif::Value::Rational(ref parts) = exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let degrees = parts.first()?.to_f64();
+    let minutes = parts.get(1).map_or(0.0, |r| r.to_f64());
+    let seconds = parts.get(2).map_or(0.0, |r| r.to_f64());
+    let value = degrees + minutes / 60.0 + seconds / 3600.0;
+    if !value.is_finite() {
+        return None;
+    }
+    let reference = exif_text(exif, reference_tag)?;
+    Some(if reference.to_uppercase().starts_with(negative) { -value } else { value })
+}
+
+/// Meters above sea level, negative below it
+fn gps_altitude(exif: &exif::Exif) -> Option<f64> {
+    let exif::Value::Rational(ref parts) = exif.get_field(Tag::GPSAltitude, In::PRIMARY)?.value else {
+        return None;
+    };
+    let altitude = parts.first()?.to_f64();
+    if !altitude.is_finite() {
+        return None;
+    }
+    let below_sea_level = exif
+        .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        == Some(1);
+    Some(if below_sea_level { -altitude } else { altitude })
+}
+
+/// Six decimals locate a point to about 10 cm
+fn round_coordinate(degrees: f64) -> f64 {
+    (degrees * 1_000_000.0).round() / 1_000_000.0
+}
+
+/// A string in a PDF: the bytes between its delimiters
+#[derive(Debug, Clone, PartialEq)]
+struct PdfString {
+    key: &'static str,
+    content: Range<usize>,
+    hex: bool,
+}
+
+/// Literal and hex string values of the info dictionary keys, in file order, so the
+/// last one is that of the newest incremental update. Values in compressed object
+/// streams are not seen.
+fn pdf_info_strings(data: &[u8]) -> Vec<PdfString> {
+    let mut strings = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = data[position..].iter().position(|&b| b == b'/') {
+        let name_start = position + offset + 1;
+        position = name_start;
+        let Some(&(key, _)) = PDF_INFO_KEYS
+            .iter()
+            .find(|(key, _)| data[name_start..].starts_with(key.as_bytes()))
+        else {
+            continue;
+        };
+
+        let mut value_start = name_start + key.len();
+        if data.get(value_start).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            // A longer name such as /CreatorTool
+            continue;
+        }
+        while data.get(value_start).is_some_and(|b| b.is_ascii_whitespace()) {
+            value_start += 1;
+        }
+        let string = match data.get(value_start) {
+            Some(b'(') => literal_string_end(data, value_start + 1).map(|end| (end, false)),
+            Some(b'<') if data.get(value_start + 1) != Some(&b'<') => data[value_start + 1..]
+                .iter()
+                .position(|&b| b == b'>')
+                .map(|end| (value_start + 1 + end, true)),
+            _ => None,
+        };
+        if let Some((end, hex)) = string {
+            strings.push(PdfString { key, content: value_start + 1..end, hex });
+            position = end;
+        }
+    }
+    strings
+}
+
+/// Index of the parenthesis closing a literal string whose content starts at `start`
+fn literal_string_end(data: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 1;
+    let mut i = start;
+    while i < data.len() {
+        match data[i] {
+            b'\\' => i += 1,
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+fn read_pdf_info(data: &[u8], metadata: &mut Map<String, Value>) {
+    for string in pdf_info_strings(data) {
+        let raw = &data[string.content];
+        let bytes = if string.hex { decode_pdf_hex(raw) } else { decode_pdf_literal(raw) };
+        let text = decode_pdf_text(&bytes);
+        if text.is_empty() {
+            continue;
+        }
+        if let Some((_, key)) = PDF_INFO_KEYS.iter().find(|(name, _)| *name == string.key) {
+            metadata.insert(key.to_string(), Value::String(text));
+        }
+    }
+}
+
+/// Resolve the escapes of a literal string
+fn decode_pdf_literal(raw: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] != b'\\' {
+            bytes.push(raw[i]);
+            i += 1;
+            continue;
+        }
+        i += 1;
+        let Some(&escaped) = raw.get(i) else {
+            break;
+        };
+        match escaped {
+            b'n' => bytes.push(b'\n'),
+            b'r' => bytes.push(b'\r'),
+            b't' => bytes.push(b'\t'),
+            b'b' => bytes.push(0x08),
+            b'f' => bytes.push(0x0C),
+            b'0'..=b'7' => {
+                let mut value: u32 = 0;
+                let mut digits = 0;
+                while digits < 3 && raw.get(i).is_some_and(|b| (b'0'..=b'7').contains(b)) {
+                    value = value * 8 + u32::from(raw[i] - b'0');
+                    i += 1;
+                    digits += 1;
+                }
+                bytes.push(value as u8);
+                continue;
+            }
+            // A backslash at the end of a line continues the string on the next
+            b'\r' => {
+                if raw.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+            }
+            b'\n' => {}
+            other => bytes.push(other),
+        }
+        i += 1;
+    }
+    bytes
+}
+
+fn decode_pdf_hex(raw: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = raw
+        .iter()
+        .filter_map(|&b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    // A missing last digit counts as 0
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] * 16 + pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+/// Text strings are UTF-16BE after a byte order mark, UTF-8 after one, and
+/// otherwise PDFDocEncoding, which is read as Latin-1
+fn decode_pdf_text(bytes: &[u8]) -> String {
+    let text: String = if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+        char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+    } else if let Some(utf8) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        String::from_utf8_lossy(utf8).into_owned()
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    };
+    text.trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string()
+}
+
+/// Byte ranges of the XMP packets (`<x:xmpmeta>` elements) in a file, in file order
+fn xmp_packet_ranges(data: &[u8]) -> Vec<Range<usize>> {
+    let mut packets = Vec::new();
+    let mut position = 0;
+    while let Some(start) = find(data, b"<x:xmpmeta", position).or_else(|| find(data, b"<x:xapmeta", position)) {
+        let close: &[u8] = if data[start..].starts_with(b"<x:xmpmeta") { b"</x:xmpmeta>" } else { b"</x:xapmeta>" };
+        let Some(end) = find(data, close, start) else {
+            break;
+        };
+        packets.push(start..end + close.len());
+        position = end + close.len();
+    }
+    packets
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|offset| from + offset)
+}
+
+/// Properties of an XMP packet, without overwriting what the info dictionary gave
+fn read_xmp(packet: &str, metadata: &mut Map<String, Value>) {
+    for (property, key) in XMP_PROPERTIES {
+        if metadata.contains_key(*key) {
+            continue;
+        }
+        if let Some(value) = xmp_property(packet, property) {
+            metadata.insert(key.to_string(), Value::String(value));
+        }
+    }
+}
+
+/// A property written as an attribute (`xmp:CreatorTool="..."`) or as an element,
+/// of which the first item is taken when it holds a list
+fn xmp_property(packet: &str, name: &str) -> Option<String> {
+    let attribute = format!(" {}=\"", name);
+    if let Some(start) = packet.find(&attribute).map(|i| i + attribute.len()) {
+        let value = &packet[start..start + packet[start..].find('"')?];
+        return non_empty(unescape_xml(value));
+    }
+
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut position = 0;
+    while let Some(offset) = packet[position..].find(&open) {
+        let tag_start = position + offset + open.len();
+        position = tag_start;
+        if !packet[tag_start..].starts_with(['>', ' ', '\t', '\r', '\n']) {
+            // A longer name such as dc:creatorx
+            continue;
+        }
+        let tag_end = tag_start + packet[tag_start..].find('>')?;
+        if packet[..tag_end].ends_with('/') {
+            continue;
+        }
+        let inner = &packet[tag_end + 1..tag_end + 1 + packet[tag_end + 1..].find(&close)?];
+        let value = match inner.find("<rdf:li") {
+            Some(item) => {
+                let item_start = item + inner[item..].find('>')? + 1;
+                &inner[item_start..item_start + inner[item_start..].find("</rdf:li>")?]
+            }
+            None => inner,
+        };
+        if let Some(value) = non_empty(unescape_xml(value)) {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn non_empty(text: String) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Blank identifying info dictionary strings and replace XMP packets with empty ones,
+/// keeping every byte offset so the cross-reference table stays valid
+fn strip_pdf(data: &[u8]) -> Vec<u8> {
+    let mut stripped = data.to_vec();
+    for string in pdf_info_strings(data) {
+        if !PDF_IDENTIFYING_KEYS.contains(&string.key) {
+            continue;
+        }
+        let content = &mut stripped[string.content];
+        if string.hex {
+            // Spaces, 0x20
+            for (i, byte) in content.iter_mut().enumerate() {
+                *byte = if i % 2 == 0 { b'2' } else { b'0' };
+            }
+        } else {
+            content.fill(b' ');
+        }
+    }
+    for packet in xmp_packet_ranges(data) {
+        let replacement: &[u8] = if packet.len() >= EMPTY_XMP_PACKET.len() { EMPTY_XMP_PACKET } else { b"" };
+        let content = &mut stripped[packet];
+        content.fill(b' ');
+        content[..replacement.len()].copy_from_slice(replacement);
+    }
+    stripped
+}
+
+/// Drop the APP1 (EXIF, XMP), APP13 (IPTC) and comment segments
+fn strip_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut stripped = Vec::with_capacity(data.len());
+    stripped.extend_from_slice(&data[..2]);
+    let mut i = 2;
+    while i + 4 <= data.len() {
+        if data[i] != 0xFF {
+            return None;
+        }
+        let marker = data[i + 1];
+        match marker {
+            // Fill byte before a marker
+            0xFF => {
+                i += 1;
+                continue;
+            }
+            // Start of scan or end of image: the rest is image data
+            0xDA | 0xD9 => break,
+            // Markers without a segment
+            0x01 | 0xD0..=0xD7 => {
+                stripped.extend_from_slice(&data[i..i + 2]);
+                i += 2;
+                continue;
+            }
+            _ => {}
+        }
+        let length = usize::from(u16::from_be_bytes([data[i + 2], data[i + 3]]));
+        let end = i + 2 + length;
+        if length < 2 || end > data.len() {
+            return None;
+        }
+        if !matches!(marker, 0xE1 | 0xED | 0xFE) {
+            stripped.extend_from_slice(&data[i..end]);
+        }
+        i = end;
+    }
+    stripped.extend_from_slice(&data[i..]);
+    Some(stripped)
+}
+
+/// Drop the EXIF, text (including XMP) and modification time chunks
+fn strip_png(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+    let mut stripped = Vec::with_capacity(data.len());
+    stripped.extend_from_slice(PNG_SIGNATURE);
+    let mut i = PNG_SIGNATURE.len();
+    while i + 12 <= data.len() {
+        let length = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        // Length, type, data and CRC
+        let end = i.checked_add(12)?.checked_add(length)?;
+        if end > data.len() {
+            return None;
+        }
+        if !PNG_METADATA_CHUNKS.contains(&&data[i + 4..i + 8]) {
+            stripped.extend_from_slice(&data[i..end]);
+        }
+        i = end;
+    }
+    stripped.extend_from_slice(&data[i..]);
+    Some(stripped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description rdf:about="" xmp:CreatorTool="Scanner &amp; Co"><dc:creator><rdf:Seq><rdf:li>Jane Doe</rdf:li></rdf:Seq></dc:creator><dc:title><rdf:Alt><rdf:li xml:lang="x-default">Lease</rdf:li></rdf:Alt></dc:title></rdf:Description></rdf:RDF></x:xmpmeta>"#;
+
+    fn pdf() -> Vec<u8> {
+        format!(
+            "%PDF-1.4\n1 0 obj\n<< /Title (Lease \\(draft\\)) /Author <FEFF004A006F0065> /Creator (Writer) /CreatorTool (x) /Producer (Lib\\\n PDF) >>\nendobj\n2 0 obj\n<< /Type /Metadata /Length {} >>\nstream\n{}\nendstream\nendobj\ntrailer\n<< /Info 1 0 R >>\n%%EOF\n",
+            XMP.len(),
+            XMP
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_strip_policy() {
+        assert_eq!(StripPolicy::parse(None), Ok(StripPolicy::Never));
+        assert_eq!(StripPolicy::parse(Some(" Shared ")), Ok(StripPolicy::Shared));
+        assert_eq!(StripPolicy::parse(Some("always")), Ok(StripPolicy::Always));
+        assert!(StripPolicy::parse(Some("sometimes")).is_err());
+        assert!(StripPolicy::Always.strips_shared());
+        assert!(!StripPolicy::Shared.strips_stored());
+    }
+
+    #[test]
+    fn test_read_pdf_info_and_xmp() {
+        let metadata = read(&pdf(), "application/pdf");
+        assert_eq!(metadata["title"], "Lease (draft)");
+        assert_eq!(metadata["author"], "Joe");
+        assert_eq!(metadata["creator_tool"], "Writer");
+        assert_eq!(metadata["producer"], "Lib PDF");
+        assert!(!metadata.contains_key("subject"));
+    }
+
+    #[test]
+    fn test_read_xmp_properties() {
+        assert_eq!(xmp_property(XMP, "dc:creator").as_deref(), Some("Jane Doe"));
+        assert_eq!(xmp_property(XMP, "dc:title").as_deref(), Some("Lease"));
+        assert_eq!(xmp_property(XMP, "xmp:CreatorTool").as_deref(), Some("Scanner & Co"));
+        assert_eq!(xmp_property(XMP, "pdf:Producer"), None);
+    }
+
+    #[test]
+    fn test_decode_pdf_strings() {
+        assert_eq!(decode_pdf_literal(br"a\(b\)\101\n"), b"a(b)A\n");
+        assert_eq!(decode_pdf_hex(b"48 69 7"), b"Hip");
+        assert_eq!(decode_pdf_text(&[0xFE, 0xFF, 0x00, 0xE9]), "é");
+        assert_eq!(decode_pdf_text(&[0xE9]), "é");
+    }
+
+    #[test]
+    fn test_strip_pdf_keeps_offsets() {
+        let original = pdf();
+        let stripped = strip(&original, "application/pdf").unwrap();
+        assert_eq!(stripped.len(), original.len());
+
+        let text = String::from_utf8_lossy(&stripped);
+        assert!(text.contains("/Title (Lease \\(draft\\))"));
+        assert!(!text.contains("Writer"));
+        assert!(!text.contains("Jane Doe"));
+        assert!(text.contains(r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"/>"#));
+
+        let metadata = read(&stripped, "application/pdf");
+        assert_eq!(metadata["title"], "Lease (draft)");
+        assert!(!metadata.contains_key("author"));
+        assert!(!metadata.contains_key("producer"));
+    }
+
+    #[test]
+    fn test_strip_jpeg_segments() {
+        let jpeg = [
+            &[0xFF, 0xD8][..],
+            &[0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46][..], // APP0 (JFIF), kept
+            &[0xFF, 0xE1, 0x00, 0x06, b'E', b'x', b'i', b'f'][..], // APP1 (EXIF)
+            &[0xFF, 0xFE, 0x00, 0x04, b'h', b'i'][..], // Comment
+            &[0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9][..], // Scan
+        ]
+        .concat();
+        let stripped = strip(&jpeg, "image/jpeg").unwrap();
+        assert_eq!(
+            stripped,
+            [&[0xFF, 0xD8][..], &[0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46][..], &[0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9][..]].concat()
+        );
+        assert_eq!(strip(b"not a jpeg", "image/jpeg"), None);
+    }
+
+    #[test]
+    fn test_strip_png_chunks() {
+        let chunk = |kind: &[u8], data: &[u8]| {
+            [&(data.len() as u32).to_be_bytes()[..], kind, data, &[0u8; 4][..]].concat()
+        };
+        let header = chunk(b"IHDR", &[0; 13]);
+        let end = chunk(b"IEND", &[]);
+        let text = chunk(b"tEXt", b"Author\0Jane");
+        let exif = chunk(b"eXIf", b"MM");
+        let png = [PNG_SIGNATURE, &header[..], &text[..], &exif[..], &end[..]].concat();
+
+        let stripped = strip(&png, "image/png").unwrap();
+        assert_eq!(stripped, [PNG_SIGNATURE, &header[..], &end[..]].concat());
+        assert_eq!(strip(&png, "image/tiff"), None);
+    }
+
+    #[test]
+    fn test_images_without_exif() {
+        let png = std::fs::read("test_files/portrait_100x200.png").expect("Failed to read test image");
+        let metadata = read(&png, "image/png");
+        assert!(!metadata.contains_key("gps_latitude"));
+        assert!(!metadata.contains_key("taken_at"));
+    }
+}
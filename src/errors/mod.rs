@@ -70,13 +70,20 @@ macro_rules! impl_into_response {
                 
                 // Create HTTP response
                 let status = self.status_code();
-                let body = Json(json!({
-                    "error": self.user_message(),
+                let mut body = json!({
+                    "error": crate::services::i18n::error_message(self.error_code(), self.user_message()),
                     "code": self.error_code(),
                     "status": status.as_u16()
-                }));
+                });
+                
+                // Internal details never reach the client; an id ties the response to the log entry
+                if status.is_server_error() {
+                    let error_id = uuid::Uuid::new_v4();
+                    tracing::error!("Internal error {} ({}): {}", error_id, self.error_code(), self);
+                    body["error_id"] = json!(error_id);
+                }
                 
-                (status, body).into_response()
+                (status, Json(body)).into_response()
             }
         }
     };
@@ -204,4 +211,5 @@ pub mod user;
 pub mod source;
 pub mod label;
 pub mod settings;
-pub mod search;
\ No newline at end of file
+pub mod search;
+pub mod panic;
\ No newline at end of file
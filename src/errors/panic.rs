@@ -0,0 +1,130 @@
+//! Panic isolation for request handlers and background tasks.
+//!
+//! A panic in a handler or a spawned task must not take the server down or
+//! leave work half done without a trace. Panics are caught at the task or
+//! request boundary, logged with the context they happened in, reported to
+//! the error manager and turned into an ordinary error for the caller.
+//! Open sqlx transactions roll back when the panicking future is dropped.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use futures::FutureExt;
+use serde_json::json;
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use thiserror::Error;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::monitoring::error_management::{
+    get_error_manager, ErrorCategory, ErrorSeverity, ManagedError,
+};
+
+/// A panic caught at a task or request boundary
+#[derive(Error, Debug)]
+#[error("{context} panicked: {message}")]
+pub struct PanicError {
+    /// What was running, e.g. `OCR job <id> (document <id>)`
+    pub context: String,
+    pub message: String,
+}
+
+/// Run a future to completion, converting a panic inside it into a `PanicError`
+pub async fn catch_panic<F, T>(context: impl Into<String>, future: F) -> Result<T, PanicError>
+where
+    F: Future<Output = T>,
+{
+    match AssertUnwindSafe(future).catch_unwind().await {
+        Ok(value) => Ok(value),
+        Err(payload) => {
+            let panic = PanicError {
+                context: context.into(),
+                message: panic_message(payload.as_ref()),
+            };
+            report(&panic);
+            Err(panic)
+        }
+    }
+}
+
+/// `tokio::spawn` for fire-and-forget tasks: a panic is logged with `context`
+/// instead of disappearing with the dropped `JoinHandle`
+pub fn spawn_guarded<F>(context: impl Into<String>, future: F) -> tokio::task::JoinHandle<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let context = context.into();
+    tokio::spawn(async move {
+        let _ = catch_panic(context, future).await;
+    })
+}
+
+/// Response for a panic caught by the HTTP layer. The panic message stays in
+/// the logs; the client gets an error id to quote when reporting the problem.
+pub fn panic_response(payload: Box<dyn Any + Send + 'static>) -> Response {
+    let error_id = Uuid::new_v4();
+    report(&PanicError {
+        context: format!("Request handler (error id {})", error_id),
+        message: panic_message(payload.as_ref()),
+    });
+
+    let status = StatusCode::INTERNAL_SERVER_ERROR;
+    (
+        status,
+        Json(json!({
+            "error": "An internal error occurred",
+            "code": "INTERNAL_SERVER_ERROR",
+            "status": status.as_u16(),
+            "error_id": error_id,
+        })),
+    )
+        .into_response()
+}
+
+/// Text of a panic payload (`panic!` with a literal or a formatted message)
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+fn report(panic: &PanicError) {
+    error!("{}", panic);
+
+    let managed_error = ManagedError {
+        category: ErrorCategory::Internal,
+        severity: ErrorSeverity::Critical,
+        code: "PANIC".to_string(),
+        user_message: "An internal error occurred".to_string(),
+        technical_details: panic.to_string(),
+        suggested_action: None,
+        suppression_key: None,
+    };
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        runtime.spawn(async move {
+            get_error_manager().handle_error(managed_error).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_catch_panic_converts_panic_to_error() {
+        let result = catch_panic("test task", async { panic!("boom {}", 42) }).await;
+        let error = result.unwrap_err();
+        assert_eq!(error.context, "test task");
+        assert_eq!(error.message, "boom 42");
+
+        assert_eq!(catch_panic("test task", async { 7 }).await.unwrap(), 7);
+    }
+}
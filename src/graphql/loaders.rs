@@ -0,0 +1,53 @@
+//! Batch loading of the data nested under lists of documents, so a page of documents
+//! with their labels and sources takes one query per kind instead of one per document.
+
+use async_graphql::dataloader::{DataLoader, Loader};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::Source;
+use crate::routes::labels::Label;
+
+pub struct LabelLoader {
+    db: Database,
+}
+
+impl Loader<Uuid> for LabelLoader {
+    type Value = Vec<Label>;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, document_ids: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let labels = self.db.get_labels_for_documents(document_ids).await.map_err(Arc::new)?;
+        Ok(labels.into_iter().collect())
+    }
+}
+
+/// Sources of the viewer; documents from other users' sources get none
+pub struct SourceLoader {
+    db: Database,
+    user_id: Uuid,
+}
+
+impl Loader<Uuid> for SourceLoader {
+    type Value = Source;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, source_ids: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let sources = self.db.get_sources(self.user_id).await.map_err(Arc::new)?;
+        Ok(sources
+            .into_iter()
+            .filter(|source| source_ids.contains(&source.id))
+            .map(|source| (source.id, source))
+            .collect())
+    }
+}
+
+pub fn label_loader(db: Database) -> DataLoader<LabelLoader> {
+    DataLoader::new(LabelLoader { db }, tokio::spawn)
+}
+
+pub fn source_loader(db: Database, user_id: Uuid) -> DataLoader<SourceLoader> {
+    DataLoader::new(SourceLoader { db, user_id }, tokio::spawn)
+}
@@ -0,0 +1,85 @@
+//! Read-only GraphQL view of a user's library: documents with their labels, source,
+//! knowledge graph and entities, and the entities' relationships to follow from there.
+//! Built with the `graphql` feature and served at `/api/graphql`.
+//!
+//! Every resolver applies the same access rules as the REST endpoint for the same data.
+//! Queries are limited in depth and complexity so one request cannot walk the whole graph.
+
+mod loaders;
+mod query;
+mod types;
+
+use async_graphql::{EmptyMutation, EmptySubscription, Schema};
+use once_cell::sync::Lazy;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::User;
+
+pub use query::QueryRoot;
+
+/// Deepest nesting of fields in a query
+pub const MAX_QUERY_DEPTH: usize = 12;
+/// Highest complexity of a query; each field counts 1, lists count per requested item
+pub const MAX_QUERY_COMPLEXITY: usize = 5_000;
+/// Most items a list field returns
+pub const MAX_PAGE_SIZE: i64 = 100;
+pub const DEFAULT_PAGE_SIZE: i64 = 25;
+
+pub type ReadurSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// The schema is the same for every request; the database and the user come with the request
+pub static SCHEMA: Lazy<ReadurSchema> = Lazy::new(|| {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+});
+
+/// The user a query runs as
+#[derive(Clone)]
+pub struct Viewer {
+    pub user: User,
+    pub workspace_id: Option<Uuid>,
+}
+
+/// Execute a request for the user, with the per-request data loaders
+pub async fn execute(db: Database, viewer: Viewer, request: async_graphql::Request) -> async_graphql::Response {
+    let request = request
+        .data(loaders::label_loader(db.clone()))
+        .data(loaders::source_loader(db.clone(), viewer.user.id))
+        .data(db)
+        .data(viewer);
+    SCHEMA.execute(request).await
+}
+
+pub(crate) fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_exposes_the_library() {
+        let sdl = SCHEMA.sdl();
+        for type_name in ["type Document ", "type Label ", "type Source ", "type Entity ", "type DocumentPage "] {
+            assert!(sdl.contains(type_name), "{}", type_name);
+        }
+        assert!(!sdl.contains("type Mutation"));
+    }
+
+    #[tokio::test]
+    async fn test_deep_traversals_are_rejected_before_resolving() {
+        let mut query = String::from("{ id }");
+        for _ in 0..MAX_QUERY_DEPTH {
+            query = format!("{{ relationships {{ entity {} }} }}", query);
+        }
+        let response = SCHEMA
+            .execute(format!("{{ entity(id: \"{}\") {} }}", Uuid::nil(), query))
+            .await;
+        assert!(!response.errors.is_empty());
+        assert!(response.errors[0].message.contains("nested too deep"), "{}", response.errors[0].message);
+    }
+}
@@ -0,0 +1,107 @@
+use async_graphql::{Context, Object, Result};
+use uuid::Uuid;
+
+use super::types::{
+    can_see_entity, internal_error, visible_document, DocumentObject, DocumentPage, EntityObject, LabelObject,
+    SourceObject,
+};
+use super::{page_size, Viewer};
+use crate::db::Database;
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A document the viewer owns or that is shared with them
+    async fn document(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<DocumentObject>> {
+        Ok(visible_document(ctx, id).await?.map(DocumentObject))
+    }
+
+    /// The viewer's documents, newest first; admins see everyone's
+    #[graphql(complexity = "page_size(limit) as usize * child_complexity")]
+    async fn documents(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<i64>,
+        #[graphql(default = 0)] offset: i64,
+        #[graphql(desc = "Only documents with this OCR status")] ocr_status: Option<String>,
+    ) -> Result<DocumentPage> {
+        let db = ctx.data::<Database>()?;
+        let viewer = ctx.data::<Viewer>()?;
+        let limit = page_size(limit);
+        let offset = offset.max(0);
+        let (user_id, role) = (viewer.user.id, viewer.user.role);
+
+        let (total_count, documents) = match ocr_status.as_deref() {
+            Some(status) => (
+                db.count_documents_by_user_with_role_and_filter(user_id, role, Some(status)).await,
+                db.get_documents_by_user_with_role_and_filter(user_id, role, Some(status), limit, offset).await,
+            ),
+            None => (
+                db.count_documents_by_user_with_role(user_id, role).await,
+                db.get_documents_by_user_with_role(user_id, role, limit, offset).await,
+            ),
+        };
+        let total_count = total_count.map_err(internal_error("count documents"))?;
+        let documents = documents.map_err(internal_error("list documents"))?;
+
+        Ok(DocumentPage {
+            total_count,
+            limit,
+            offset,
+            has_more: offset + (documents.len() as i64) < total_count,
+            items: documents.into_iter().map(DocumentObject).collect(),
+        })
+    }
+
+    /// The viewer's labels, the system labels and those of the current workspace
+    async fn labels(&self, ctx: &Context<'_>) -> Result<Vec<LabelObject>> {
+        let db = ctx.data::<Database>()?;
+        let viewer = ctx.data::<Viewer>()?;
+        let labels = db
+            .get_visible_labels(viewer.user.id, viewer.workspace_id)
+            .await
+            .map_err(internal_error("list labels"))?;
+        Ok(labels.into_iter().map(LabelObject).collect())
+    }
+
+    async fn sources(&self, ctx: &Context<'_>) -> Result<Vec<SourceObject>> {
+        let db = ctx.data::<Database>()?;
+        let viewer = ctx.data::<Viewer>()?;
+        let sources = db.get_sources(viewer.user.id).await.map_err(internal_error("list sources"))?;
+        Ok(sources.into_iter().map(SourceObject).collect())
+    }
+
+    async fn source(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<SourceObject>> {
+        let db = ctx.data::<Database>()?;
+        let viewer = ctx.data::<Viewer>()?;
+        let source = db.get_source(viewer.user.id, id).await.map_err(internal_error("load source"))?;
+        Ok(source.map(SourceObject))
+    }
+
+    /// The viewer's knowledge-graph entities, by name
+    #[graphql(complexity = "page_size(limit) as usize * child_complexity")]
+    async fn entities(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Part of the entity name")] q: Option<String>,
+        #[graphql(desc = "Only entities of this type, e.g. `Person`")] label: Option<String>,
+        limit: Option<i64>,
+    ) -> Result<Vec<EntityObject>> {
+        let db = ctx.data::<Database>()?;
+        let viewer = ctx.data::<Viewer>()?;
+        let name = q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+        let entities = db
+            .list_graph_entities(viewer.user.id, name, label.as_deref(), page_size(limit))
+            .await
+            .map_err(internal_error("list entities"))?;
+        Ok(entities.into_iter().map(|summary| EntityObject::new(summary.entity)).collect())
+    }
+
+    async fn entity(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<EntityObject>> {
+        let db = ctx.data::<Database>()?;
+        let viewer = ctx.data::<Viewer>()?;
+        let entity = db.get_graph_entity(id).await.map_err(internal_error("load entity"))?;
+        Ok(entity.filter(|entity| can_see_entity(viewer, entity)).map(EntityObject::new))
+    }
+}
@@ -0,0 +1,520 @@
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{Context, Enum, Json, Object, Result, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tokio::sync::OnceCell;
+use tracing::error;
+use uuid::Uuid;
+
+use super::loaders::{LabelLoader, SourceLoader};
+use super::{page_size, Viewer};
+use crate::db::Database;
+use crate::models::{
+    normalize_entity_name, Document, DocumentGraph, DocumentGraphEdge, DocumentGraphNode, EntityAttribute,
+    EntityMention, EntityProfile, EntityRelationship, GraphEntity, RelationshipDirection, SharePermission, Source,
+    UserRole,
+};
+use crate::routes::labels::Label;
+use crate::services::entity_service::EntityService;
+
+/// Log a failure and hide its details from the client, like the REST handlers do
+pub(super) fn internal_error<E: std::fmt::Display>(context: &'static str) -> impl FnOnce(E) -> async_graphql::Error {
+    move |e| {
+        error!("GraphQL: failed to {}: {}", context, e);
+        async_graphql::Error::new("Internal server error")
+    }
+}
+
+/// A document the viewer can see, shared documents included
+pub(super) async fn visible_document(ctx: &Context<'_>, document_id: Uuid) -> Result<Option<Document>> {
+    let db = ctx.data::<Database>()?;
+    let viewer = ctx.data::<Viewer>()?;
+    db.get_shared_document_by_id(document_id, viewer.user.id, viewer.user.role, SharePermission::View)
+        .await
+        .map_err(internal_error("load document"))
+}
+
+/// Entities are only visible to their owner and admins
+pub(super) fn can_see_entity(viewer: &Viewer, entity: &GraphEntity) -> bool {
+    entity.user_id == viewer.user.id || viewer.user.role == UserRole::Admin
+}
+
+pub struct DocumentObject(pub Document);
+
+#[Object(name = "Document")]
+impl DocumentObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn filename(&self) -> &str {
+        &self.0.filename
+    }
+
+    async fn original_filename(&self) -> &str {
+        &self.0.original_filename
+    }
+
+    async fn file_size(&self) -> i64 {
+        self.0.file_size
+    }
+
+    async fn mime_type(&self) -> &str {
+        &self.0.mime_type
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    /// `pending`, `processing`, `completed` or `failed`
+    async fn ocr_status(&self) -> Option<&str> {
+        self.0.ocr_status.as_deref()
+    }
+
+    async fn ocr_confidence(&self) -> Option<f32> {
+        self.0.ocr_confidence
+    }
+
+    async fn ocr_word_count(&self) -> Option<i32> {
+        self.0.ocr_word_count
+    }
+
+    /// The recognized text; only select it when needed, it can be large
+    async fn ocr_text(&self) -> Option<&str> {
+        self.0.ocr_text.as_deref()
+    }
+
+    async fn owner_id(&self) -> Uuid {
+        self.0.user_id
+    }
+
+    async fn source_type(&self) -> Option<&str> {
+        self.0.source_type.as_deref()
+    }
+
+    async fn source_path(&self) -> Option<&str> {
+        self.0.source_path.as_deref()
+    }
+
+    /// Metadata read from the file and its source
+    async fn metadata(&self) -> Option<Json<Value>> {
+        self.0.source_metadata.clone().map(Json)
+    }
+
+    async fn original_created_at(&self) -> Option<DateTime<Utc>> {
+        self.0.original_created_at
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    async fn labels(&self, ctx: &Context<'_>) -> Result<Vec<LabelObject>> {
+        let labels = ctx
+            .data::<DataLoader<LabelLoader>>()?
+            .load_one(self.0.id)
+            .await
+            .map_err(internal_error("load document labels"))?;
+        Ok(labels.unwrap_or_default().into_iter().map(LabelObject).collect())
+    }
+
+    /// The source the document was synced from, when it is one of the viewer's
+    async fn source(&self, ctx: &Context<'_>) -> Result<Option<SourceObject>> {
+        let Some(source_id) = self.0.source_id else {
+            return Ok(None);
+        };
+        let source = ctx
+            .data::<DataLoader<SourceLoader>>()?
+            .load_one(source_id)
+            .await
+            .map_err(internal_error("load document source"))?;
+        Ok(source.map(SourceObject))
+    }
+
+    /// The knowledge graph of the latest analysis, with corrections applied
+    async fn graph(&self, ctx: &Context<'_>) -> Result<GraphObject> {
+        let db = ctx.data::<Database>()?;
+        let graph = db.get_document_graph(self.0.id, None).await.map_err(internal_error("load document graph"))?;
+        Ok(GraphObject(graph))
+    }
+
+    /// The entities the document's graph names; only for the document's owner
+    #[graphql(complexity = "10 * child_complexity")]
+    async fn entities(&self, ctx: &Context<'_>) -> Result<Vec<EntityObject>> {
+        let db = ctx.data::<Database>()?;
+        let viewer = ctx.data::<Viewer>()?;
+        if self.0.user_id != viewer.user.id && viewer.user.role != UserRole::Admin {
+            return Ok(Vec::new());
+        }
+
+        let graph = db.get_document_graph(self.0.id, None).await.map_err(internal_error("load document graph"))?;
+        let mut wanted: Vec<(String, String)> =
+            graph.nodes.iter().map(|node| (node.label.clone(), normalize_entity_name(&node.name))).collect();
+        wanted.sort();
+        wanted.dedup();
+        let ids = db
+            .find_graph_entity_ids(self.0.user_id, &wanted)
+            .await
+            .map_err(internal_error("find document entities"))?;
+        let ids: Vec<Uuid> = ids.into_values().collect();
+        let entities = db.get_graph_entities(&ids).await.map_err(internal_error("load document entities"))?;
+        Ok(entities.into_iter().map(EntityObject::new).collect())
+    }
+}
+
+pub struct LabelObject(pub Label);
+
+#[Object(name = "Label")]
+impl LabelObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn color(&self) -> &str {
+        &self.0.color
+    }
+
+    async fn background_color(&self) -> Option<&str> {
+        self.0.background_color.as_deref()
+    }
+
+    async fn icon(&self) -> Option<&str> {
+        self.0.icon.as_deref()
+    }
+
+    /// The label this one is nested under
+    async fn parent_id(&self) -> Option<Uuid> {
+        self.0.parent_id
+    }
+
+    async fn is_system(&self) -> bool {
+        self.0.is_system
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+}
+
+/// A source without its configuration, which holds credentials
+pub struct SourceObject(pub Source);
+
+#[Object(name = "Source")]
+impl SourceObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn source_type(&self) -> String {
+        self.0.source_type.to_string()
+    }
+
+    async fn enabled(&self) -> bool {
+        self.0.enabled
+    }
+
+    async fn status(&self) -> String {
+        self.0.status.to_string()
+    }
+
+    async fn last_sync_at(&self) -> Option<DateTime<Utc>> {
+        self.0.last_sync_at
+    }
+
+    async fn last_error(&self) -> Option<&str> {
+        self.0.last_error.as_deref()
+    }
+
+    async fn total_files_synced(&self) -> i64 {
+        self.0.total_files_synced
+    }
+
+    async fn total_files_pending(&self) -> i64 {
+        self.0.total_files_pending
+    }
+
+    async fn total_size_bytes(&self) -> i64 {
+        self.0.total_size_bytes
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    /// The documents most recently synced from the source
+    #[graphql(complexity = "page_size(limit) as usize * child_complexity")]
+    async fn recent_documents(&self, ctx: &Context<'_>, limit: Option<i64>) -> Result<Vec<DocumentObject>> {
+        let db = ctx.data::<Database>()?;
+        let documents = db
+            .get_recent_documents_for_source(self.0.user_id, self.0.id, page_size(limit))
+            .await
+            .map_err(internal_error("load source documents"))?;
+        Ok(documents.into_iter().map(DocumentObject).collect())
+    }
+}
+
+pub struct GraphObject(pub DocumentGraph);
+
+#[Object(name = "DocumentGraph")]
+impl GraphObject {
+    /// None when the document has not been analyzed
+    async fn version_number(&self) -> Option<i32> {
+        self.0.version.as_ref().map(|version| version.version_number)
+    }
+
+    async fn model(&self) -> Option<&str> {
+        self.0.version.as_ref().and_then(|version| version.model.as_deref())
+    }
+
+    async fn nodes(&self) -> Vec<GraphNodeObject> {
+        self.0.nodes.iter().cloned().map(GraphNodeObject).collect()
+    }
+
+    async fn edges(&self) -> Vec<GraphEdgeObject> {
+        self.0.edges.iter().cloned().map(GraphEdgeObject).collect()
+    }
+}
+
+pub struct GraphNodeObject(DocumentGraphNode);
+
+#[Object(name = "GraphNode")]
+impl GraphNodeObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    /// The entity type, e.g. `Person`
+    async fn label(&self) -> &str {
+        &self.0.label
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn properties(&self) -> Option<Json<Value>> {
+        self.0.properties.clone().map(Json)
+    }
+}
+
+pub struct GraphEdgeObject(DocumentGraphEdge);
+
+#[Object(name = "GraphEdge")]
+impl GraphEdgeObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn source_node_id(&self) -> Uuid {
+        self.0.source
+    }
+
+    async fn target_node_id(&self) -> Uuid {
+        self.0.target
+    }
+
+    async fn relationship(&self) -> &str {
+        &self.0.relationship
+    }
+
+    async fn properties(&self) -> Option<Json<Value>> {
+        self.0.properties.clone().map(Json)
+    }
+}
+
+/// An entity of the knowledge graph; its relationships and attributes come from the
+/// same profile as its REST page, built once per entity in a query
+pub struct EntityObject {
+    entity: GraphEntity,
+    profile: OnceCell<EntityProfile>,
+}
+
+impl EntityObject {
+    pub fn new(entity: GraphEntity) -> Self {
+        Self { entity, profile: OnceCell::new() }
+    }
+
+    async fn profile(&self, ctx: &Context<'_>) -> Result<&EntityProfile> {
+        let db = ctx.data::<Database>()?;
+        self.profile
+            .get_or_try_init(|| async { EntityService::new(db.clone()).profile(self.entity.clone()).await })
+            .await
+            .map_err(internal_error("build entity profile"))
+    }
+}
+
+#[Object(name = "Entity")]
+impl EntityObject {
+    async fn id(&self) -> Uuid {
+        self.entity.id
+    }
+
+    /// The entity type, e.g. `Person`
+    async fn label(&self) -> &str {
+        &self.entity.label
+    }
+
+    async fn name(&self) -> &str {
+        &self.entity.name
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.entity.created_at
+    }
+
+    /// Documents mentioning the entity, most recent first
+    #[graphql(complexity = "page_size(limit) as usize * child_complexity")]
+    async fn documents(&self, ctx: &Context<'_>, limit: Option<i64>) -> Result<Vec<EntityMentionObject>> {
+        let db = ctx.data::<Database>()?;
+        let mentions = db
+            .find_entity_documents(&self.entity, page_size(limit))
+            .await
+            .map_err(internal_error("load entity documents"))?;
+        Ok(mentions.into_iter().map(EntityMentionObject).collect())
+    }
+
+    /// Relationships to other entities, merged across documents, most documented first
+    #[graphql(complexity = "50 * child_complexity")]
+    async fn relationships(&self, ctx: &Context<'_>) -> Result<Vec<RelationshipObject>> {
+        let profile = self.profile(ctx).await?;
+        Ok(profile.relationships.iter().cloned().map(RelationshipObject).collect())
+    }
+
+    /// Attributes with every value found for them
+    #[graphql(complexity = 50)]
+    async fn attributes(&self, ctx: &Context<'_>) -> Result<Vec<EntityAttributeObject>> {
+        let profile = self.profile(ctx).await?;
+        Ok(profile.attributes.iter().map(EntityAttributeObject::from).collect())
+    }
+}
+
+pub struct EntityMentionObject(EntityMention);
+
+#[Object(name = "EntityMention")]
+impl EntityMentionObject {
+    async fn document_id(&self) -> Uuid {
+        self.0.document_id
+    }
+
+    async fn filename(&self) -> &str {
+        &self.0.filename
+    }
+
+    /// When the document was created at its source, or else uploaded
+    async fn document_date(&self) -> DateTime<Utc> {
+        self.0.document_date
+    }
+
+    async fn document(&self, ctx: &Context<'_>) -> Result<Option<DocumentObject>> {
+        Ok(visible_document(ctx, self.0.document_id).await?.map(DocumentObject))
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "RelationshipDirection")]
+pub enum Direction {
+    /// The entity is the source: "entity RELATIONSHIP other"
+    Outgoing,
+    /// The entity is the target: "other RELATIONSHIP entity"
+    Incoming,
+}
+
+pub struct RelationshipObject(EntityRelationship);
+
+#[Object(name = "EntityRelationship")]
+impl RelationshipObject {
+    async fn direction(&self) -> Direction {
+        match self.0.direction {
+            RelationshipDirection::Outgoing => Direction::Outgoing,
+            RelationshipDirection::Incoming => Direction::Incoming,
+        }
+    }
+
+    async fn relationship(&self) -> &str {
+        &self.0.relationship
+    }
+
+    /// Type of the other entity
+    async fn label(&self) -> &str {
+        &self.0.label
+    }
+
+    /// Name of the other entity
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn document_ids(&self) -> &[Uuid] {
+        &self.0.document_ids
+    }
+
+    /// The other entity, to follow the graph further
+    async fn entity(&self, ctx: &Context<'_>) -> Result<Option<EntityObject>> {
+        let Some(entity_id) = self.0.entity_id else {
+            return Ok(None);
+        };
+        let db = ctx.data::<Database>()?;
+        let viewer = ctx.data::<Viewer>()?;
+        let entity = db.get_graph_entity(entity_id).await.map_err(internal_error("load entity"))?;
+        Ok(entity.filter(|entity| can_see_entity(viewer, entity)).map(EntityObject::new))
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "EntityAttributeValue")]
+pub struct EntityAttributeValueObject {
+    value: Json<Value>,
+    /// Documents the value was extracted from
+    document_ids: Vec<Uuid>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "EntityAttribute")]
+pub struct EntityAttributeObject {
+    key: String,
+    values: Vec<EntityAttributeValueObject>,
+}
+
+impl From<&EntityAttribute> for EntityAttributeObject {
+    fn from(attribute: &EntityAttribute) -> Self {
+        Self {
+            key: attribute.key.clone(),
+            values: attribute
+                .values
+                .iter()
+                .map(|value| EntityAttributeValueObject {
+                    value: Json(value.value.clone()),
+                    document_ids: value.document_ids.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A page of documents
+#[derive(SimpleObject)]
+#[graphql(name = "DocumentPage")]
+pub struct DocumentPage {
+    pub total_count: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
+    pub items: Vec<DocumentObject>,
+}
@@ -263,6 +263,10 @@ async fn process_single_file(
             info!("Created new document for batch file {}: {}", file_info.name, doc.id);
             Ok(Some((doc.id, file_info.size)))
         }
+        IngestionResult::NewVersion(doc) => {
+            info!("Stored batch file {} as a new version of document {}", file_info.name, doc.id);
+            Ok(Some((doc.id, file_info.size)))
+        }
         IngestionResult::Skipped { existing_document_id, reason } => {
             info!("Skipped duplicate batch file {}: {} (existing: {})", file_info.name, reason, existing_document_id);
             Ok(None) // File was skipped due to deduplication
@@ -0,0 +1,532 @@
+//! Bulk import for the initial migration of large archives.
+//!
+//! Files are hashed in parallel, deduplicated against each other and the user's
+//! existing documents, stored, and inserted with batched statements. OCR queueing
+//! and thumbnail generation are deferred until every file is in and then paced,
+//! so the import does not flood the workers. Every outcome is appended to a
+//! manifest, and re-running the same import skips files the manifest already covers.
+
+use anyhow::{anyhow, Result};
+use chrono::DateTime;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::{
+    config::Config,
+    db::Database,
+    models::{Document, UserRole},
+    ocr::queue::OcrQueueService,
+    services::file_service::FileService,
+};
+
+/// Manifest written next to the imported files unless another path is given
+pub const DEFAULT_MANIFEST_NAME: &str = ".readur-import-manifest.jsonl";
+const SOURCE_TYPE: &str = "bulk_import";
+/// Upper bound keeps a batch insert below Postgres' bind parameter limit
+const MAX_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct BulkImportOptions {
+    /// Files hashed and stored at the same time
+    pub concurrency: usize,
+    /// Documents per insert statement
+    pub batch_size: usize,
+    /// Documents queued for OCR per minute once the import is done (0 = no limit)
+    pub ocr_rate_per_minute: u32,
+    /// Generate thumbnails while queueing instead of on first view
+    pub generate_thumbnails: bool,
+    pub manifest_path: Option<PathBuf>,
+}
+
+impl Default for BulkImportOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            batch_size: 500,
+            ocr_rate_per_minute: 600,
+            generate_thumbnails: false,
+            manifest_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestStatus {
+    /// Stored and inserted, waiting to be queued for OCR
+    Imported,
+    /// Queued for OCR; nothing left to do
+    Queued,
+    Duplicate,
+    Skipped,
+    Failed,
+}
+
+/// One line of the manifest. Later lines for the same path replace earlier ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: i64,
+    pub modified: i64,
+    pub status: ManifestStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ManifestEntry {
+    /// Whether this entry still describes the file and needs no further import work
+    fn covers(&self, size: i64, modified: i64) -> bool {
+        self.size == size && self.modified == modified && self.status != ManifestStatus::Failed
+    }
+}
+
+/// Append-only record of what an import has done so far
+pub struct ImportManifest {
+    entries: HashMap<String, ManifestEntry>,
+    file: tokio::fs::File,
+}
+
+impl ImportManifest {
+    pub async fn open(path: &Path) -> Result<Self> {
+        let entries = match tokio::fs::read_to_string(path).await {
+            Ok(contents) => parse_manifest(&contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(anyhow!("Failed to read import manifest {}: {}", path.display(), e)),
+        };
+
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| anyhow!("Failed to open import manifest {}: {}", path.display(), e))?;
+
+        Ok(Self { entries, file })
+    }
+
+    pub fn get(&self, path: &str) -> Option<&ManifestEntry> {
+        self.entries.get(path)
+    }
+
+    pub fn with_status(&self, status: ManifestStatus) -> Vec<ManifestEntry> {
+        self.entries.values().filter(|e| e.status == status).cloned().collect()
+    }
+
+    pub async fn record(&mut self, entries: Vec<ManifestEntry>) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = String::new();
+        for entry in &entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+        self.file.write_all(lines.as_bytes()).await?;
+        self.file.sync_data().await?;
+
+        for entry in entries {
+            self.entries.insert(entry.path.clone(), entry);
+        }
+        Ok(())
+    }
+}
+
+/// Latest entry per path; a torn last line from an interrupted run is ignored
+pub fn parse_manifest(contents: &str) -> HashMap<String, ManifestEntry> {
+    let mut entries = HashMap::new();
+    for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<ManifestEntry>(line) {
+            Ok(entry) => {
+                entries.insert(entry.path.clone(), entry);
+            }
+            Err(e) => warn!("Ignoring unreadable import manifest line: {}", e),
+        }
+    }
+    entries
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct BulkImportSummary {
+    pub found: usize,
+    pub already_done: usize,
+    pub imported: usize,
+    pub duplicates: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub queued: usize,
+}
+
+struct Candidate {
+    path: PathBuf,
+    size: i64,
+    modified: i64,
+}
+
+pub struct BulkImporter {
+    db: Database,
+    queue_service: OcrQueueService,
+    file_service: FileService,
+    config: Config,
+    options: BulkImportOptions,
+}
+
+impl BulkImporter {
+    pub fn new(
+        db: Database,
+        queue_service: OcrQueueService,
+        file_service: FileService,
+        config: Config,
+        options: BulkImportOptions,
+    ) -> Self {
+        Self { db, queue_service, file_service, config, options }
+    }
+
+    pub async fn import_directory(&self, dir_path: &Path, user_id: Uuid) -> Result<BulkImportSummary> {
+        let manifest_path = self
+            .options
+            .manifest_path
+            .clone()
+            .unwrap_or_else(|| dir_path.join(DEFAULT_MANIFEST_NAME));
+        let mut manifest = ImportManifest::open(&manifest_path).await?;
+        let mut summary = BulkImportSummary::default();
+
+        let pending = self.scan(dir_path, &manifest_path, &manifest, &mut summary);
+        info!(
+            "Found {} files, {} already imported according to {}",
+            summary.found,
+            summary.already_done,
+            manifest_path.display()
+        );
+
+        let batch_size = self.options.batch_size.clamp(1, MAX_BATCH_SIZE);
+        let mut seen_hashes = HashSet::new();
+        for (index, batch) in pending.chunks(batch_size).enumerate() {
+            let entries = self.import_batch(batch, user_id, &mut seen_hashes).await?;
+            for entry in &entries {
+                match entry.status {
+                    ManifestStatus::Imported => summary.imported += 1,
+                    ManifestStatus::Duplicate => summary.duplicates += 1,
+                    ManifestStatus::Skipped => summary.skipped += 1,
+                    ManifestStatus::Failed => summary.failed += 1,
+                    ManifestStatus::Queued => {}
+                }
+            }
+            manifest.record(entries).await?;
+            info!(
+                "Progress: {}/{} files ({} imported, {} duplicates, {} failed)",
+                (index * batch_size + batch.len()).min(pending.len()),
+                pending.len(),
+                summary.imported,
+                summary.duplicates,
+                summary.failed
+            );
+        }
+
+        summary.queued = self.queue_imported(&mut manifest, user_id).await?;
+        Ok(summary)
+    }
+
+    /// Allowed files under `dir_path` that the manifest does not already cover
+    fn scan(
+        &self,
+        dir_path: &Path,
+        manifest_path: &Path,
+        manifest: &ImportManifest,
+        summary: &mut BulkImportSummary,
+    ) -> Vec<Candidate> {
+        let mut pending = Vec::new();
+
+        for entry in WalkDir::new(dir_path).follow_links(true).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() || entry.path() == manifest_path {
+                continue;
+            }
+            let filename = entry.file_name().to_string_lossy().to_string();
+            if !self.file_service.is_allowed_file_type(&filename, &self.config.allowed_file_types) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else { continue };
+
+            summary.found += 1;
+            let candidate = Candidate {
+                path: entry.path().to_path_buf(),
+                size: metadata.len() as i64,
+                modified: modified_secs(&metadata),
+            };
+
+            let key = candidate.path.to_string_lossy();
+            if manifest.get(&key).is_some_and(|e| e.covers(candidate.size, candidate.modified)) {
+                summary.already_done += 1;
+            } else {
+                pending.push(candidate);
+            }
+        }
+
+        pending
+    }
+
+    async fn import_batch(
+        &self,
+        batch: &[Candidate],
+        user_id: Uuid,
+        seen_hashes: &mut HashSet<String>,
+    ) -> Result<Vec<ManifestEntry>> {
+        let max_size = self.config.max_file_size_mb as i64 * 1024 * 1024;
+        let mut entries = Vec::with_capacity(batch.len());
+
+        let hashed: Vec<(&Candidate, Result<String>)> = stream::iter(batch)
+            .map(|candidate| async move { (candidate, hash_file(candidate.path.clone()).await) })
+            .buffer_unordered(self.options.concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut unique = Vec::new();
+        for (candidate, hash) in hashed {
+            if candidate.size > max_size {
+                entries.push(entry_for(candidate, ManifestStatus::Skipped, None, None, Some("file too large")));
+                continue;
+            }
+            match hash {
+                Ok(hash) if seen_hashes.insert(hash.clone()) => unique.push((candidate, hash)),
+                Ok(hash) => entries.push(entry_for(candidate, ManifestStatus::Duplicate, Some(hash), None, None)),
+                Err(e) => entries.push(entry_for(candidate, ManifestStatus::Failed, None, None, Some(&e.to_string()))),
+            }
+        }
+
+        let hashes: Vec<String> = unique.iter().map(|(_, hash)| hash.clone()).collect();
+        let existing: HashSet<String> = self.db.get_existing_file_hashes(user_id, &hashes).await?.into_iter().collect();
+        let (duplicates, new_files): (Vec<_>, Vec<_>) =
+            unique.into_iter().partition(|(_, hash)| existing.contains(hash));
+        for (candidate, hash) in duplicates {
+            entries.push(entry_for(candidate, ManifestStatus::Duplicate, Some(hash), None, None));
+        }
+
+        let stored: Vec<(&Candidate, String, Result<Document>)> = stream::iter(new_files)
+            .map(|(candidate, hash)| async move {
+                let document = self.store_file(candidate, &hash, user_id).await;
+                (candidate, hash, document)
+            })
+            .buffer_unordered(self.options.concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut documents = Vec::new();
+        for (candidate, hash, document) in stored {
+            match document {
+                Ok(document) => documents.push((candidate, hash, document)),
+                Err(e) => entries.push(entry_for(candidate, ManifestStatus::Failed, Some(hash), None, Some(&e.to_string()))),
+            }
+        }
+
+        let rows: Vec<Document> = documents.iter().map(|(_, _, document)| document.clone()).collect();
+        let inserted: HashSet<Uuid> = self.db.insert_documents_batch(&rows).await?.into_iter().collect();
+        for (candidate, hash, document) in documents {
+            if inserted.contains(&document.id) {
+                entries.push(entry_for(candidate, ManifestStatus::Imported, Some(hash), Some(document.id), None));
+            } else {
+                // Another upload added the same content while this batch was being stored
+                if let Err(e) = self.file_service.delete_document_files(&document).await {
+                    warn!("Failed to remove stored copy of duplicate {}: {}", candidate.path.display(), e);
+                }
+                entries.push(entry_for(candidate, ManifestStatus::Duplicate, Some(hash), None, None));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn store_file(&self, candidate: &Candidate, hash: &str, user_id: Uuid) -> Result<Document> {
+        let data = tokio::fs::read(&candidate.path).await?;
+        let filename = candidate
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mime_type = mime_guess::from_path(&filename).first_or_octet_stream().to_string();
+
+        let document_id = Uuid::new_v4();
+        let file_path = self
+            .file_service
+            .save_document_file(user_id, document_id, &filename, &data)
+            .await?;
+
+        Ok(self.file_service.create_document_with_id(
+            document_id,
+            &filename,
+            &filename,
+            &file_path,
+            data.len() as i64,
+            &mime_type,
+            user_id,
+            Some(hash.to_string()),
+            None,
+            DateTime::from_timestamp(candidate.modified, 0),
+            Some(candidate.path.to_string_lossy().to_string()),
+            Some(SOURCE_TYPE.to_string()),
+            None,
+            None,
+            None,
+            None,
+            None,
+        ))
+    }
+
+    /// Queue imported documents for OCR at the configured rate, including ones an
+    /// interrupted run imported but never queued
+    async fn queue_imported(&self, manifest: &mut ImportManifest, user_id: Uuid) -> Result<usize> {
+        let mut pending = manifest.with_status(ManifestStatus::Imported);
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        pending.sort_by(|a, b| a.path.cmp(&b.path));
+        info!("Queueing {} imported documents for OCR", pending.len());
+
+        let rate = self.options.ocr_rate_per_minute;
+        let chunk_size = if rate == 0 { MAX_BATCH_SIZE } else { (rate as usize).clamp(1, MAX_BATCH_SIZE) };
+        let mut queued = 0;
+
+        for chunk in pending.chunks(chunk_size) {
+            let started = std::time::Instant::now();
+            let items: Vec<(Uuid, i32, i64)> = chunk
+                .iter()
+                .filter_map(|entry| Some((entry.document_id?, ocr_priority(entry.size), entry.size)))
+                .collect();
+            self.queue_service.enqueue_documents_batch(items).await?;
+
+            if self.options.generate_thumbnails {
+                self.generate_thumbnails(chunk, user_id).await;
+            }
+
+            let done: Vec<ManifestEntry> = chunk
+                .iter()
+                .cloned()
+                .map(|entry| ManifestEntry { status: ManifestStatus::Queued, ..entry })
+                .collect();
+            queued += done.len();
+            manifest.record(done).await?;
+            info!("Queued {}/{} documents for OCR", queued, pending.len());
+
+            if let Some(pause) = pacing_delay(chunk.len(), rate, started.elapsed()) {
+                if queued < pending.len() {
+                    tokio::time::sleep(pause).await;
+                }
+            }
+        }
+
+        Ok(queued)
+    }
+
+    async fn generate_thumbnails(&self, entries: &[ManifestEntry], user_id: Uuid) {
+        stream::iter(entries)
+            .for_each_concurrent(self.options.concurrency.max(1), |entry| async move {
+                let Some(document_id) = entry.document_id else { return };
+                let Ok(Some(document)) = self.db.get_document_by_id(document_id, user_id, UserRole::User).await else { return };
+                if let Err(e) = self
+                    .file_service
+                    .get_or_generate_thumbnail(&document.file_path, &document.filename, &document.mime_type)
+                    .await
+                {
+                    warn!("Thumbnail generation failed for {}: {}", document.filename, e);
+                }
+            })
+            .await;
+    }
+}
+
+async fn hash_file(path: PathBuf) -> Result<String> {
+    tokio::task::spawn_blocking(move || -> Result<String> {
+        let mut file = std::fs::File::open(&path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await
+    .map_err(|e| anyhow!("Hashing task panicked: {}", e))?
+}
+
+fn modified_secs(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn entry_for(
+    candidate: &Candidate,
+    status: ManifestStatus,
+    sha256: Option<String>,
+    document_id: Option<Uuid>,
+    error: Option<&str>,
+) -> ManifestEntry {
+    ManifestEntry {
+        path: candidate.path.to_string_lossy().to_string(),
+        size: candidate.size,
+        modified: candidate.modified,
+        status,
+        sha256,
+        document_id,
+        error: error.map(str::to_string),
+    }
+}
+
+/// Smaller files first, so most of the archive becomes searchable early
+fn ocr_priority(file_size: i64) -> i32 {
+    const MB: i64 = 1024 * 1024;
+    match file_size {
+        s if s <= MB => 10,
+        s if s <= 10 * MB => 6,
+        _ => 2,
+    }
+}
+
+/// Time left to wait so that `count` items take at least a minute per `rate_per_minute`
+pub fn pacing_delay(count: usize, rate_per_minute: u32, elapsed: Duration) -> Option<Duration> {
+    if rate_per_minute == 0 {
+        return None;
+    }
+    let budget = Duration::from_secs_f64(count as f64 * 60.0 / rate_per_minute as f64);
+    budget.checked_sub(elapsed).filter(|d| !d.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest_keeps_latest_entry() {
+        let contents = concat!(
+            r#"{"path":"/in/a.pdf","size":10,"modified":5,"status":"imported","document_id":"00000000-0000-0000-0000-000000000001"}"#, "\n",
+            r#"{"path":"/in/b.pdf","size":20,"modified":5,"status":"failed","error":"permission denied"}"#, "\n",
+            r#"{"path":"/in/a.pdf","size":10,"modified":5,"status":"queued","document_id":"00000000-0000-0000-0000-000000000001"}"#, "\n",
+            r#"{"path":"/in/c.pdf","si"#,
+        );
+
+        let entries = parse_manifest(contents);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries["/in/a.pdf"].status, ManifestStatus::Queued);
+        assert!(entries["/in/a.pdf"].covers(10, 5));
+        assert!(!entries["/in/a.pdf"].covers(11, 5));
+        assert!(!entries["/in/b.pdf"].covers(20, 5));
+    }
+
+    #[test]
+    fn test_pacing_delay() {
+        assert_eq!(pacing_delay(100, 0, Duration::ZERO), None);
+        assert_eq!(pacing_delay(60, 120, Duration::from_secs(10)), Some(Duration::from_secs(20)));
+        assert_eq!(pacing_delay(60, 120, Duration::from_secs(45)), None);
+    }
+}
@@ -12,9 +12,28 @@ use tracing::{debug, warn};
 use serde_json;
 use chrono::Utc;
 
-use crate::models::{Document, FileIngestionInfo};
+use crate::models::{Document, FileIngestionInfo, ParsedFilename, PDF_SECURITY_METADATA_KEY, PDF_SIGNATURES_METADATA_KEY};
 use crate::db::Database;
+use crate::embedded_metadata;
+use crate::mime_detection::{self, UnsupportedFileType};
+use crate::services::document_progress;
+use crate::services::document_version_service::DocumentVersionService;
 use crate::services::file_service::FileService;
+use crate::services::failed_document_service;
+use crate::services::filename_template;
+use crate::services::ingestion_hook_service::{IngestionHookRejected, IngestionHookService, PreConsumeFile};
+use crate::services::label_rule_service::LabelRuleService;
+use crate::services::malware_scan_service::{self, MalwareDetected, ScanVerdict};
+use crate::services::pdf_security_service::{self, PdfSecurityService};
+use crate::services::pdf_signature_service;
+use crate::services::storage_layout::{LayoutFields, StoragePathTemplate};
+use crate::services::storage_quota_service::StorageQuotaService;
+use crate::services::thumbnail_service;
+use crate::utils::geo;
+
+/// Source types whose paths are made up rather than locations in a source, so a
+/// file at the same path is not a new version of the same document
+const VIRTUAL_PATH_SOURCES: &[&str] = &["web_upload"];
 
 #[derive(Debug, Clone)]
 pub enum DeduplicationPolicy {
@@ -38,6 +57,9 @@ pub enum IngestionResult {
     Skipped { existing_document_id: Uuid, reason: String },
     /// Document was tracked as duplicate (for WebDAV)
     TrackedAsDuplicate { existing_document_id: Uuid },
+    /// A changed file at a known source path was stored as a new version of its
+    /// document, which now needs OCR again
+    NewVersion(Document),
 }
 
 #[derive(Debug)]
@@ -118,13 +140,58 @@ impl DocumentIngestionService {
     }
 
     /// Unified document ingestion with configurable deduplication policy
-    pub async fn ingest_document(&self, request: DocumentIngestionRequest) -> Result<IngestionResult, Box<dyn std::error::Error + Send + Sync>> {
-        let file_hash = self.calculate_file_hash(&request.file_data);
-        let file_size = request.file_data.len() as i64;
-        
+    #[tracing::instrument(
+        name = "document.ingest",
+        skip_all,
+        fields(filename = %request.filename, size = request.file_data.len(), source_type = ?request.source_type)
+    )]
+    pub async fn ingest_document(&self, mut request: DocumentIngestionRequest) -> Result<IngestionResult, Box<dyn std::error::Error + Send + Sync>> {
         // Clone source_type early for error handling
         let source_type_for_error = request.source_type.clone();
 
+        // Pre-consume hooks may replace the file, so they see it before anything else
+        if let Err(e) = self.run_pre_consume_hooks(&mut request).await {
+            if let Some(rejected) = e.downcast_ref::<IngestionHookRejected>() {
+                warn!("Refusing {}: {}", request.filename, rejected);
+                self.record_hook_rejection(&request, &rejected.to_string()).await;
+            }
+            return Err(e);
+        }
+
+        // The content decides the type; what the client or source claimed is only kept
+        let detected = mime_detection::detect_upload_mime(&request.file_data, &request.filename, Some(&request.mime_type));
+        let claimed_mime_type = std::mem::replace(&mut request.mime_type, detected.mime_type);
+        if !mime_detection::is_allowed_mime_type(&request.mime_type) {
+            let error = UnsupportedFileType {
+                filename: request.original_filename.clone(),
+                mime_type: request.mime_type.clone(),
+            };
+            warn!("Refusing {}: {}", request.filename, error);
+            self.record_unsupported_type(&request, &claimed_mime_type, &error.to_string()).await;
+            return Err(Box::new(error));
+        }
+        let claimed_mime_type = (claimed_mime_type != request.mime_type)
+            .then_some(serde_json::Value::String(claimed_mime_type));
+
+        // Embedded metadata is read before it may be stripped from the stored file
+        let strip_policy = embedded_metadata::configured()
+            .map_err(|e| format!("Metadata stripping is misconfigured: {}", e))?;
+        let embedded = embedded_metadata::read(&request.file_data, &request.mime_type);
+        request.source_metadata = with_embedded_metadata(request.source_metadata.take(), embedded);
+        if strip_policy.strips_stored() {
+            if let Some(stripped) = embedded_metadata::strip(&request.file_data, &request.mime_type) {
+                request.file_data = stripped;
+                request.source_metadata = with_metadata(
+                    request.source_metadata.take(),
+                    "metadata_stripped",
+                    Some(serde_json::Value::Bool(true)),
+                );
+            }
+        }
+
+        let file_hash = self.calculate_file_hash(&request.file_data);
+        let file_size = request.file_data.len() as i64;
+
         debug!(
             "Ingesting document: {} for user {} (hash: {}, size: {} bytes, policy: {:?})",
             request.filename, request.user_id, &file_hash[..8], file_size, request.deduplication_policy
@@ -168,25 +235,93 @@ impl DocumentIngestionService {
             }
         }
 
+        // Files that do not fit in the owner's storage quota are refused
+        match StorageQuotaService::new(self.db.clone())
+            .check(request.user_id, &request.original_filename, file_size)
+            .await
+        {
+            Ok(Some(exceeded)) => {
+                warn!("Refusing {}: {}", request.filename, exceeded);
+                return Err(Box::new(exceeded));
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to check the storage quota of user {}: {}", request.user_id, e),
+        }
+
+        // Nothing is stored before the malware scanner has seen it
+        let malware_scan = self.scan_for_malware(&request, &file_hash).await?;
+
+        // Signed PDFs keep who signed them and whether the signatures hold up
+        let pdf_signatures = if request.mime_type == "application/pdf" {
+            pdf_signature_service::ingestion_report(&request.file_data, &file_hash).await
+        } else {
+            None
+        };
+
+        // Embedded scripts, launch actions and attachments are recorded, and stripped
+        // when that is configured
+        let pdf_security = if request.mime_type == "application/pdf" {
+            pdf_security_service::ingestion_scan(&request.file_data, &file_hash, pdf_signatures.is_some()).await
+        } else {
+            None
+        };
+
+        // A changed file at a path this source ingested before is a new version
+        if let Some(source_path) = request.source_path.as_deref() {
+            let source_type = request.source_type.as_deref();
+            if !source_type.is_some_and(|t| VIRTUAL_PATH_SOURCES.contains(&t)) {
+                let existing = self.db
+                    .find_document_by_source_path(request.user_id, source_type, request.source_id, source_path)
+                    .await?;
+                if let Some(existing_doc) = existing {
+                    debug!(
+                        "File {} changed since it was ingested as document {}, storing a new version",
+                        source_path, existing_doc.id
+                    );
+                    let document = DocumentVersionService::new(self.db.clone(), self.file_service.clone())
+                        .add_version(&existing_doc, &request.file_data, request.original_modified_at)
+                        .await?;
+                    return Ok(IngestionResult::NewVersion(document));
+                }
+            }
+        }
+
+        // Names the source's filename templates recognize pre-populate the metadata
+        let parsed_filename = self.parse_source_filename(&mut request).await;
+
         // Generate document ID upfront so we can use it for storage path
         let document_id = Uuid::new_v4();
         
-        // Save file to storage - use S3 if configured, otherwise local storage
-        let file_path = match self.file_service
-            .save_document_file(request.user_id, document_id, &request.filename, &request.file_data)
-            .await {
+        // Save file to storage - where the storage layout template puts it, otherwise
+        // in the backend's own layout
+        let layout_path = self.storage_layout_path(&request, document_id, parsed_filename.as_ref()).await;
+        let stored = match &layout_path {
+            Some(relative_path) => {
+                self.file_service
+                    .save_library_file(request.user_id, relative_path, &request.file_data)
+                    .await
+            }
+            None => {
+                self.file_service
+                    .store_document_content(request.user_id, document_id, &request.filename, &request.file_data, &file_hash)
+                    .await
+            }
+        };
+        let file_path = match stored {
                 Ok(path) => path,
                 Err(e) => {
                     warn!("Failed to save file {}: {}", request.filename, e);
                     
-                    // Create failed document record for storage failure
+                    // Create failed document record for storage failure, keeping the file
+                    // locally so it can be requeued once storage works again
+                    let failed_id = Uuid::new_v4();
                     let failed_document = crate::models::FailedDocument {
-                        id: Uuid::new_v4(),
+                        id: failed_id,
                         user_id: request.user_id,
                         filename: request.filename.clone(),
                         original_filename: Some(request.original_filename.clone()),
                         original_path: None,
-                        file_path: None, // couldn't save
+                        file_path: failed_document_service::keep_failed_file(&self.file_service, failed_id, &request.file_data).await,
                         file_size: Some(file_size),
                         file_hash: Some(file_hash.clone()),
                         mime_type: Some(request.mime_type.clone()),
@@ -215,6 +350,15 @@ impl DocumentIngestionService {
                 }
             };
 
+        let source_metadata = [
+            ("malware_scan", malware_scan),
+            ("claimed_mime_type", claimed_mime_type),
+            (PDF_SIGNATURES_METADATA_KEY, pdf_signatures),
+            (PDF_SECURITY_METADATA_KEY, pdf_security.as_ref().and_then(|scan| serde_json::to_value(&scan.report).ok())),
+        ]
+        .into_iter()
+        .fold(request.source_metadata, |metadata, (key, value)| with_metadata(metadata, key, value));
+
         // Create document record with the same ID used for storage
         let document = self.file_service.create_document_with_id(
             document_id,
@@ -233,7 +377,7 @@ impl DocumentIngestionService {
             request.file_permissions,
             request.file_owner,
             request.file_group,
-            request.source_metadata,
+            source_metadata,
         );
 
         let saved_document = match self.db.create_document(document).await {
@@ -245,6 +389,11 @@ impl DocumentIngestionService {
                    && error_string.contains("idx_documents_user_file_hash") {
                     warn!("Hash collision detected during concurrent upload for {} (hash: {}), fetching existing document", 
                           request.filename, &file_hash[..8]);
+
+                    // The content is not kept for this document after all
+                    if let Err(release_err) = self.file_service.release_blob(&file_path).await {
+                        warn!("Failed to release blob {}: {}", file_path, release_err);
+                    }
                     
                     // Race condition: another request created the document, fetch it
                     match self.db.get_document_by_user_and_hash(request.user_id, &file_hash).await {
@@ -308,9 +457,301 @@ impl DocumentIngestionService {
             saved_document.original_filename, saved_document.id, request.user_id
         );
 
+        // The stripped copy becomes the contents, with the original as the first version
+        let saved_document = match pdf_security {
+            Some(pdf_security_service::IngestionScan { report, sanitized: Some(sanitized) }) => {
+                PdfSecurityService::new(self.db.clone(), self.file_service.clone())
+                    .keep_sanitized(saved_document, &sanitized, report)
+                    .await
+            }
+            _ => saved_document,
+        };
+
+        // Sources that embargo what they ingest keep it from other users from the start
+        if let Some(source_id) = request.source_id {
+            if let Err(e) = self.db.apply_source_embargo(saved_document.id, source_id).await {
+                warn!("Failed to apply the embargo of source {} to document {}: {}", source_id, saved_document.id, e);
+            }
+        }
+
+        // Rules on the source and MIME type apply right away; rules on the text wait for OCR
+        if let Err(e) = LabelRuleService::new(self.db.clone()).apply_at_ingestion(&saved_document).await {
+            warn!("Failed to apply label rules to document {}: {}", saved_document.id, e);
+        }
+
+        if let Some(parsed) = &parsed_filename {
+            self.apply_parsed_filename(&saved_document, parsed).await;
+        }
+
+        // Geotagged files can be found by where they were captured
+        let coordinates = saved_document.source_metadata.as_ref().and_then(geo::coordinates_from_metadata);
+        if let Some((latitude, longitude)) = coordinates {
+            if let Err(e) = self.db.set_document_coordinates(saved_document.id, latitude, longitude).await {
+                warn!("Failed to record the coordinates of document {}: {}", saved_document.id, e);
+            }
+        }
+
+        // Rendered in the background, so browsing does not wait for it
+        thumbnail_service::requeue_thumbnail(&self.db, saved_document.id).await;
+
+        document_progress::ingested(saved_document.user_id, saved_document.id);
         Ok(IngestionResult::Created(saved_document))
     }
 
+    /// Apply the first of the source's filename templates that matches the file name:
+    /// the title names the document and the date becomes its creation date. The rest of
+    /// what was parsed is applied once the document exists.
+    async fn parse_source_filename(&self, request: &mut DocumentIngestionRequest) -> Option<ParsedFilename> {
+        let source_id = request.source_id?;
+        let templates = match self.db.get_source_filename_templates(source_id).await {
+            Ok(templates) => templates?.templates,
+            Err(e) => {
+                warn!("Failed to load the filename templates of source {}: {}", source_id, e);
+                return None;
+            }
+        };
+        let templates = match filename_template::compile(&templates) {
+            Ok(templates) => templates,
+            Err(e) => {
+                warn!("Filename templates of source {} are invalid: {}", source_id, e);
+                return None;
+            }
+        };
+
+        let parsed = filename_template::parse_filename(&templates, &request.original_filename)?;
+        debug!("Parsed {} with filename template '{}'", request.original_filename, parsed.template);
+        if let Some(title) = &parsed.title {
+            request.filename = filename_template::title_filename(title, &request.original_filename);
+        }
+        if let Some(date) = parsed.date {
+            request.original_created_at = date.and_hms_opt(0, 0, 0).map(|date| date.and_utc());
+        }
+        request.source_metadata = with_metadata(
+            request.source_metadata.take(),
+            "filename_fields",
+            serde_json::to_value(&parsed).ok(),
+        );
+        Some(parsed)
+    }
+
+    /// Where the storage layout template puts a new document's file, when a template is
+    /// set and files are not shared blobs. Metadata assigned after ingestion only moves
+    /// the file with the next relocation.
+    async fn storage_layout_path(
+        &self,
+        request: &DocumentIngestionRequest,
+        document_id: Uuid,
+        parsed: Option<&ParsedFilename>,
+    ) -> Option<String> {
+        if self.file_service.deduplicates() {
+            return None;
+        }
+        let template = match self.db.get_storage_layout().await {
+            Ok(layout) => layout.template?,
+            Err(e) => {
+                warn!("Failed to load the storage layout: {}", e);
+                return None;
+            }
+        };
+        let template = match StoragePathTemplate::parse(&template) {
+            Ok(template) => template,
+            Err(e) => {
+                warn!("Storage layout template is invalid: {}", e);
+                return None;
+            }
+        };
+        let username = match self.db.get_user_by_id(request.user_id).await {
+            Ok(user) => user.map(|user| user.username).unwrap_or_default(),
+            Err(e) => {
+                warn!("Failed to load user {} for the storage layout: {}", request.user_id, e);
+                String::new()
+            }
+        };
+
+        Some(template.render(&LayoutFields {
+            document_id,
+            filename: &request.filename,
+            username: &username,
+            date: request.original_created_at.unwrap_or_else(Utc::now),
+            correspondent: parsed.and_then(|parsed| parsed.correspondent.as_deref()),
+            document_type: parsed.and_then(|parsed| parsed.document_type.as_deref()),
+        }))
+    }
+
+    /// Give a new document the correspondent, document type and labels parsed from its name
+    async fn apply_parsed_filename(&self, document: &Document, parsed: &ParsedFilename) {
+        if let Some(name) = &parsed.correspondent {
+            let assigned = async {
+                let correspondent_id = self.db.ensure_correspondent(document.user_id, name).await?;
+                self.db
+                    .assign_correspondent(document.user_id, &[document.id], Some(correspondent_id), "rule")
+                    .await
+            };
+            if let Err(e) = assigned.await {
+                warn!("Failed to assign correspondent '{}' to document {}: {}", name, document.id, e);
+            }
+        }
+
+        if let Some(name) = &parsed.document_type {
+            let assigned = async {
+                let document_type = self.db.ensure_document_type(document.user_id, name, &[]).await?;
+                self.db
+                    .set_document_custom_fields(document.id, document_type.id, &serde_json::Map::new())
+                    .await
+            };
+            if let Err(e) = assigned.await {
+                warn!("Failed to set document type '{}' of document {}: {}", name, document.id, e);
+            }
+        }
+
+        for tag in &parsed.tags {
+            if let Err(e) = self.db.assign_label_by_name(document.user_id, document.id, tag).await {
+                warn!("Failed to label document {} with '{}': {}", document.id, tag, e);
+            }
+        }
+    }
+
+    /// Record a file refused for its type as a failed document, so it shows up with
+    /// the other files that could not be ingested
+    async fn record_unsupported_type(
+        &self,
+        request: &DocumentIngestionRequest,
+        claimed_mime_type: &str,
+        error_message: &str,
+    ) {
+        let failed_id = Uuid::new_v4();
+        let failed_document = crate::models::FailedDocument {
+            id: failed_id,
+            user_id: request.user_id,
+            filename: request.filename.clone(),
+            original_filename: Some(request.original_filename.clone()),
+            original_path: request.source_path.clone(),
+            file_path: failed_document_service::keep_failed_file(&self.file_service, failed_id, &request.file_data).await,
+            file_size: Some(request.file_data.len() as i64),
+            file_hash: Some(self.calculate_file_hash(&request.file_data)),
+            mime_type: Some(request.mime_type.clone()),
+            content: None,
+            tags: Vec::new(),
+            ocr_text: None,
+            ocr_confidence: None,
+            ocr_word_count: None,
+            ocr_processing_time_ms: None,
+            failure_reason: "unsupported_format".to_string(),
+            failure_stage: "ingestion".to_string(),
+            existing_document_id: None,
+            ingestion_source: request.source_type.clone().unwrap_or_else(|| "upload".to_string()),
+            error_message: Some(format!("{} (claimed type: {})", error_message, claimed_mime_type)),
+            retry_count: Some(0),
+            last_retry_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        if let Err(e) = self.db.create_failed_document(failed_document).await {
+            warn!("Failed to create failed document record for unsupported type: {}", e);
+        }
+    }
+
+    /// Run the pre-consume hooks over the request's file, replacing it with what they
+    /// made of it
+    async fn run_pre_consume_hooks(
+        &self,
+        request: &mut DocumentIngestionRequest,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let file = PreConsumeFile {
+            filename: &request.original_filename,
+            mime_type: &request.mime_type,
+            user_id: request.user_id,
+            source_type: request.source_type.as_deref(),
+            data: &request.file_data,
+        };
+        if let Some(data) = IngestionHookService::new(self.db.clone()).run_pre_consume(file).await? {
+            request.file_data = data;
+        }
+        Ok(())
+    }
+
+    /// Record a file refused by a pre-consume hook as a failed document
+    async fn record_hook_rejection(&self, request: &DocumentIngestionRequest, error_message: &str) {
+        let failed_id = Uuid::new_v4();
+        let failed_document = crate::models::FailedDocument {
+            id: failed_id,
+            user_id: request.user_id,
+            filename: request.filename.clone(),
+            original_filename: Some(request.original_filename.clone()),
+            original_path: request.source_path.clone(),
+            file_path: failed_document_service::keep_failed_file(&self.file_service, failed_id, &request.file_data).await,
+            file_size: Some(request.file_data.len() as i64),
+            file_hash: Some(self.calculate_file_hash(&request.file_data)),
+            mime_type: Some(request.mime_type.clone()),
+            content: None,
+            tags: Vec::new(),
+            ocr_text: None,
+            ocr_confidence: None,
+            ocr_word_count: None,
+            ocr_processing_time_ms: None,
+            failure_reason: "policy_violation".to_string(),
+            failure_stage: "validation".to_string(),
+            existing_document_id: None,
+            ingestion_source: request.source_type.clone().unwrap_or_else(|| "upload".to_string()),
+            error_message: Some(error_message.to_string()),
+            retry_count: Some(0),
+            last_retry_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        if let Err(e) = self.db.create_failed_document(failed_document).await {
+            warn!("Failed to create failed document record for hook rejection: {}", e);
+        }
+    }
+
+    /// Scan the file when a scanner is configured. Infected files are quarantined and
+    /// refused with `MalwareDetected`; otherwise the result to record is returned.
+    async fn scan_for_malware(
+        &self,
+        request: &DocumentIngestionRequest,
+        file_hash: &str,
+    ) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error + Send + Sync>> {
+        let scanner = match malware_scan_service::configured() {
+            Ok(Some(scanner)) => scanner,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(format!("Malware scanning is misconfigured: {}", e).into()),
+        };
+
+        match scanner.scan(&request.file_data).await {
+            Ok(ScanVerdict::Clean) => Ok(Some(malware_scan_service::scan_metadata("clean", None))),
+            Ok(ScanVerdict::Infected(signature)) => {
+                let file = crate::models::QuarantinedFile {
+                    id: Uuid::new_v4(),
+                    user_id: request.user_id,
+                    filename: request.filename.clone(),
+                    original_filename: request.original_filename.clone(),
+                    file_path: String::new(),
+                    file_size: request.file_data.len() as i64,
+                    file_hash: file_hash.to_string(),
+                    mime_type: request.mime_type.clone(),
+                    signature: signature.clone(),
+                    source_type: request.source_type.clone(),
+                    source_id: request.source_id,
+                    source_path: request.source_path.clone(),
+                    created_at: Utc::now(),
+                };
+                let quarantined = malware_scan_service::quarantine(&self.db, &self.file_service, file, &request.file_data).await?;
+                Err(Box::new(MalwareDetected {
+                    filename: request.original_filename.clone(),
+                    signature,
+                    quarantine_id: quarantined.id,
+                }))
+            }
+            Err(e) if scanner.fail_open() => {
+                warn!("Ingesting {} unscanned, the malware scan failed: {}", request.filename, e);
+                Ok(Some(malware_scan_service::scan_metadata("unscanned", Some(&e.to_string()))))
+            }
+            Err(e) => Err(format!("Malware scan of {} failed: {}", request.filename, e).into()),
+        }
+    }
+
     /// Calculate SHA256 hash of file content
     fn calculate_file_hash(&self, data: &[u8]) -> String {
         let mut hasher = Sha256::new();
@@ -473,4 +914,37 @@ impl DocumentIngestionService {
     }
 }
 
-// TODO: Add comprehensive tests once test_helpers module is available
\ No newline at end of file
+/// Add the metadata embedded in the file, without overwriting what the source provided
+fn with_embedded_metadata(
+    source_metadata: Option<serde_json::Value>,
+    embedded: serde_json::Map<String, serde_json::Value>,
+) -> Option<serde_json::Value> {
+    if embedded.is_empty() {
+        return source_metadata;
+    }
+    let mut metadata = source_metadata.unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = metadata.as_object_mut() {
+        for (key, value) in embedded {
+            object.entry(key).or_insert(value);
+        }
+    }
+    Some(metadata)
+}
+
+/// Record an ingestion fact, such as the malware scan result, in the document's metadata
+fn with_metadata(
+    source_metadata: Option<serde_json::Value>,
+    key: &str,
+    value: Option<serde_json::Value>,
+) -> Option<serde_json::Value> {
+    let Some(value) = value else {
+        return source_metadata;
+    };
+    let mut metadata = source_metadata.unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(key.to_string(), value);
+    }
+    Some(metadata)
+}
+
+// TODO: Add comprehensive tests once test_helpers module is available
@@ -1,2 +1,4 @@
+pub mod bulk_import;
 pub mod batch_ingest;
-pub mod document_ingestion;
\ No newline at end of file
+pub mod document_ingestion;
+pub mod paperless_import;
\ No newline at end of file
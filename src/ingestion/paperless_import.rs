@@ -0,0 +1,591 @@
+//! Import of a Paperless-ngx export (`document_exporter`) into Readur.
+//!
+//! Reads `manifest.json` and, for exports made with `--split-manifest`, the
+//! per-document `*-manifest.json` files. Tags become labels with their colors and
+//! correspondents become Readur correspondents. Correspondents, document types,
+//! custom fields and notes are also kept in the document's source metadata, and
+//! Paperless' OCR text and timestamps are kept so the documents do not need to be
+//! processed again. Re-running an import skips documents whose content is already
+//! present.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use tracing::{info, warn};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::{
+    db::Database,
+    ingestion::document_ingestion::{DeduplicationPolicy, DocumentIngestionRequest, DocumentIngestionService, IngestionResult},
+    ocr::queue::OcrQueueService,
+    services::file_service::FileService,
+};
+
+const SOURCE_TYPE: &str = "paperless_import";
+const MAIN_MANIFEST: &str = "manifest.json";
+const SPLIT_MANIFEST_SUFFIX: &str = "-manifest.json";
+
+#[derive(Debug, Clone, Default)]
+pub struct PaperlessImportOptions {
+    /// Import Paperless' archived PDF/A version instead of the original file when there is one
+    pub prefer_archive: bool,
+    /// Queue every document for OCR instead of keeping Paperless' text
+    pub reocr: bool,
+    /// Also label documents with their correspondent, as `Correspondent: <name>`
+    pub correspondent_labels: bool,
+    /// Also label documents with their document type, as `Document type: <name>`
+    pub document_type_labels: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PaperlessImportSummary {
+    pub documents: usize,
+    pub imported: usize,
+    /// Content already in Readur; labels were still applied
+    pub already_present: usize,
+    pub missing_files: usize,
+    pub failed: usize,
+    pub queued: usize,
+}
+
+/// One object of an export manifest, in Django's serialization format
+#[derive(Debug, Deserialize)]
+struct ManifestRecord {
+    model: String,
+    #[serde(default)]
+    pk: Value,
+    #[serde(default)]
+    fields: Map<String, Value>,
+    #[serde(rename = "__exported_file_name__")]
+    exported_file_name: Option<String>,
+    #[serde(rename = "__exported_archive_name__")]
+    exported_archive_name: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DocumentFields {
+    #[serde(default)]
+    title: String,
+    content: Option<String>,
+    created: Option<String>,
+    modified: Option<String>,
+    added: Option<String>,
+    correspondent: Option<i64>,
+    document_type: Option<i64>,
+    #[serde(default)]
+    tags: Vec<i64>,
+    mime_type: Option<String>,
+    original_filename: Option<String>,
+    archive_serial_number: Option<i64>,
+    /// `gpg` for documents of old, encrypted installations
+    storage_type: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaperlessDocument {
+    pub id: i64,
+    pub title: String,
+    pub content: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub added: Option<DateTime<Utc>>,
+    pub correspondent: Option<i64>,
+    pub document_type: Option<i64>,
+    pub tags: Vec<i64>,
+    pub mime_type: Option<String>,
+    pub original_filename: Option<String>,
+    pub archive_serial_number: Option<i64>,
+    pub encrypted: bool,
+    /// Paths relative to the export directory
+    pub file_name: Option<String>,
+    pub archive_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaperlessTag {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct CustomField {
+    name: String,
+    select_options: Vec<Value>,
+}
+
+/// Everything an import needs from an export's manifests
+#[derive(Debug, Default)]
+pub struct PaperlessExport {
+    pub documents: Vec<PaperlessDocument>,
+    pub tags: HashMap<i64, PaperlessTag>,
+    pub correspondents: HashMap<i64, String>,
+    pub document_types: HashMap<i64, String>,
+    custom_fields: HashMap<i64, CustomField>,
+    /// Document id to (custom field id, raw value)
+    custom_field_values: HashMap<i64, Vec<(i64, Value)>>,
+    notes: HashMap<i64, Vec<Value>>,
+}
+
+impl PaperlessExport {
+    pub fn load(export_dir: &Path) -> Result<Self> {
+        let main_manifest = export_dir.join(MAIN_MANIFEST);
+        if !main_manifest.is_file() {
+            return Err(anyhow!("{} has no {}; is it a Paperless-ngx export?", export_dir.display(), MAIN_MANIFEST));
+        }
+
+        let mut manifests = vec![main_manifest];
+        for entry in WalkDir::new(export_dir).into_iter().filter_map(|e| e.ok()) {
+            let is_split_manifest = entry.file_type().is_file()
+                && entry.file_name().to_string_lossy().ends_with(SPLIT_MANIFEST_SUFFIX);
+            if is_split_manifest {
+                manifests.push(entry.into_path());
+            }
+        }
+
+        let mut records = Vec::new();
+        for manifest in &manifests {
+            let contents = std::fs::read_to_string(manifest)?;
+            let parsed: Vec<ManifestRecord> = serde_json::from_str(&contents)
+                .map_err(|e| anyhow!("Cannot parse {}: {}", manifest.display(), e))?;
+            records.extend(parsed);
+        }
+
+        Ok(Self::from_records(records))
+    }
+
+    fn from_records(records: Vec<ManifestRecord>) -> Self {
+        let mut export = Self::default();
+
+        for record in records {
+            let Some(pk) = record.pk.as_i64() else {
+                continue;
+            };
+            let name = || record.fields.get("name").and_then(Value::as_str).unwrap_or("").trim().to_string();
+
+            match record.model.as_str() {
+                "documents.document" => {
+                    let fields: DocumentFields = match serde_json::from_value(Value::Object(record.fields.clone())) {
+                        Ok(fields) => fields,
+                        Err(e) => {
+                            warn!("Skipping Paperless document {} with unexpected fields: {}", pk, e);
+                            continue;
+                        }
+                    };
+                    export.documents.push(PaperlessDocument {
+                        id: pk,
+                        title: fields.title,
+                        content: fields.content,
+                        created: fields.created.as_deref().and_then(parse_timestamp),
+                        modified: fields.modified.as_deref().and_then(parse_timestamp),
+                        added: fields.added.as_deref().and_then(parse_timestamp),
+                        correspondent: fields.correspondent,
+                        document_type: fields.document_type,
+                        tags: fields.tags,
+                        mime_type: fields.mime_type,
+                        original_filename: fields.original_filename,
+                        archive_serial_number: fields.archive_serial_number,
+                        encrypted: fields.storage_type.as_deref() == Some("gpg"),
+                        file_name: record.exported_file_name,
+                        archive_name: record.exported_archive_name,
+                    });
+                }
+                "documents.tag" => {
+                    let color = record.fields.get("color").and_then(Value::as_str).and_then(normalize_color);
+                    export.tags.insert(pk, PaperlessTag { name: name(), color });
+                }
+                "documents.correspondent" => {
+                    export.correspondents.insert(pk, name());
+                }
+                "documents.documenttype" => {
+                    export.document_types.insert(pk, name());
+                }
+                "documents.customfield" => {
+                    let select_options = record
+                        .fields
+                        .get("extra_data")
+                        .and_then(|extra| extra.get("select_options"))
+                        .and_then(Value::as_array)
+                        .cloned()
+                        .unwrap_or_default();
+                    export.custom_fields.insert(pk, CustomField { name: name(), select_options });
+                }
+                "documents.customfieldinstance" => {
+                    let document = record.fields.get("document").and_then(Value::as_i64);
+                    let field = record.fields.get("field").and_then(Value::as_i64);
+                    if let (Some(document), Some(field)) = (document, field) {
+                        let value = record
+                            .fields
+                            .iter()
+                            .find(|(key, value)| key.starts_with("value_") && !value.is_null())
+                            .map(|(_, value)| value.clone())
+                            .unwrap_or(Value::Null);
+                        export.custom_field_values.entry(document).or_default().push((field, value));
+                    }
+                }
+                "documents.note" => {
+                    if let Some(document) = record.fields.get("document").and_then(Value::as_i64) {
+                        export.notes.entry(document).or_default().push(json!({
+                            "note": record.fields.get("note"),
+                            "created": record.fields.get("created"),
+                        }));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        export.documents.sort_by_key(|document| document.id);
+        export
+    }
+
+    /// Custom field name to value, with select options resolved to their labels
+    fn custom_fields_of(&self, document_id: i64) -> Map<String, Value> {
+        let mut values = Map::new();
+        for (field_id, value) in self.custom_field_values.get(&document_id).into_iter().flatten() {
+            let Some(field) = self.custom_fields.get(field_id) else {
+                continue;
+            };
+            values.insert(field.name.clone(), resolve_select_option(&field.select_options, value));
+        }
+        values
+    }
+
+    /// The document's metadata as stored in `source_metadata.paperless`
+    fn metadata_of(&self, document: &PaperlessDocument) -> Value {
+        json!({
+            "id": document.id,
+            "title": document.title,
+            "correspondent": document.correspondent.and_then(|id| self.correspondents.get(&id)),
+            "document_type": document.document_type.and_then(|id| self.document_types.get(&id)),
+            "archive_serial_number": document.archive_serial_number,
+            "added": document.added,
+            "custom_fields": self.custom_fields_of(document.id),
+            "notes": self.notes.get(&document.id).cloned().unwrap_or_default(),
+        })
+    }
+}
+
+/// Paperless stores select values as the option's id (2.x) or its index (1.x)
+fn resolve_select_option(options: &[Value], value: &Value) -> Value {
+    if options.is_empty() {
+        return value.clone();
+    }
+    let option = match value {
+        Value::String(id) => options.iter().find(|option| option.get("id").and_then(Value::as_str) == Some(id.as_str())),
+        Value::Number(index) => index.as_u64().and_then(|index| options.get(index as usize)),
+        _ => None,
+    };
+    match option {
+        Some(Value::String(label)) => Value::String(label.clone()),
+        Some(option) => option.get("label").cloned().unwrap_or_else(|| value.clone()),
+        None => value.clone(),
+    }
+}
+
+/// Paperless serializes `created` as a date (2.x) or a datetime (older versions)
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|timestamp| timestamp.and_utc())
+}
+
+/// Labels hold `#rrggbb` colors
+fn normalize_color(color: &str) -> Option<String> {
+    let hex = color.strip_prefix('#')?;
+    (hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit())).then(|| color.to_lowercase())
+}
+
+/// A relative path inside the export, refusing anything that would leave it
+fn export_path(export_dir: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| export_dir.join(relative))
+}
+
+/// Readur shows documents by filename, Paperless by title: name the document after
+/// its title, keeping the stored file's extension
+fn display_filename(document: &PaperlessDocument, stored_name: &str) -> String {
+    let title: String = document.title.trim().chars().map(|c| if c == '/' || c == '\\' { '-' } else { c }).collect();
+    if title.is_empty() {
+        return stored_name.to_string();
+    }
+    match Path::new(stored_name).extension().and_then(|ext| ext.to_str()) {
+        Some(extension) if !title.to_lowercase().ends_with(&format!(".{}", extension.to_lowercase())) => {
+            format!("{}.{}", title, extension)
+        }
+        _ => title,
+    }
+}
+
+pub struct PaperlessImporter {
+    db: Database,
+    queue_service: OcrQueueService,
+    ingestion_service: DocumentIngestionService,
+    options: PaperlessImportOptions,
+}
+
+enum DocumentOutcome {
+    Imported { queued: bool },
+    AlreadyPresent,
+    MissingFile,
+}
+
+impl PaperlessImporter {
+    pub fn new(db: Database, queue_service: OcrQueueService, file_service: FileService, options: PaperlessImportOptions) -> Self {
+        let ingestion_service = DocumentIngestionService::new(db.clone(), file_service);
+        Self { db, queue_service, ingestion_service, options }
+    }
+
+    pub async fn import_export(&self, export_dir: &Path, user_id: Uuid) -> Result<PaperlessImportSummary> {
+        let export = PaperlessExport::load(export_dir)?;
+        info!(
+            "Paperless export has {} documents, {} tags, {} correspondents and {} document types",
+            export.documents.len(),
+            export.tags.len(),
+            export.correspondents.len(),
+            export.document_types.len()
+        );
+
+        let mut tag_labels = HashMap::new();
+        for (tag_id, tag) in &export.tags {
+            if !tag.name.is_empty() {
+                let label_id = self.db.ensure_label(user_id, &tag.name, tag.color.as_deref()).await?;
+                tag_labels.insert(*tag_id, label_id);
+            }
+        }
+
+        let mut summary = PaperlessImportSummary {
+            documents: export.documents.len(),
+            ..Default::default()
+        };
+        for (index, document) in export.documents.iter().enumerate() {
+            match self.import_document(&export, export_dir, document, user_id, &tag_labels).await {
+                Ok(DocumentOutcome::Imported { queued }) => {
+                    summary.imported += 1;
+                    if queued {
+                        summary.queued += 1;
+                    }
+                }
+                Ok(DocumentOutcome::AlreadyPresent) => summary.already_present += 1,
+                Ok(DocumentOutcome::MissingFile) => summary.missing_files += 1,
+                Err(e) => {
+                    warn!("Paperless document {} ('{}') failed: {}", document.id, document.title, e);
+                    summary.failed += 1;
+                }
+            }
+            if (index + 1) % 100 == 0 {
+                info!("Processed {}/{} Paperless documents", index + 1, export.documents.len());
+            }
+        }
+
+        Ok(summary)
+    }
+
+    async fn import_document(
+        &self,
+        export: &PaperlessExport,
+        export_dir: &Path,
+        document: &PaperlessDocument,
+        user_id: Uuid,
+        tag_labels: &HashMap<i64, Uuid>,
+    ) -> Result<DocumentOutcome> {
+        if document.encrypted {
+            return Err(anyhow!("document is GPG-encrypted; decrypt the Paperless installation before exporting"));
+        }
+
+        let archive = document.archive_name.as_deref().filter(|_| self.options.prefer_archive);
+        let Some(stored_name) = archive.or(document.file_name.as_deref()) else {
+            warn!("Paperless document {} has no exported file", document.id);
+            return Ok(DocumentOutcome::MissingFile);
+        };
+        let path = export_path(export_dir, stored_name)
+            .ok_or_else(|| anyhow!("exported file name '{}' points outside the export", stored_name))?;
+        if !path.is_file() {
+            warn!("Exported file {} of Paperless document {} is missing", path.display(), document.id);
+            return Ok(DocumentOutcome::MissingFile);
+        }
+        let file_data = tokio::fs::read(&path).await?;
+        let file_size = file_data.len() as i64;
+
+        // An archived version is always a PDF, whatever the original was
+        let mime_type = match (archive, document.mime_type.as_deref()) {
+            (Some(_), _) => "application/pdf".to_string(),
+            (None, Some(mime_type)) => mime_type.to_string(),
+            (None, None) => mime_guess::from_path(&path).first_or_octet_stream().to_string(),
+        };
+        let original_filename = document
+            .original_filename
+            .clone()
+            .unwrap_or_else(|| path.file_name().unwrap_or_default().to_string_lossy().into_owned());
+
+        let request = DocumentIngestionRequest {
+            filename: display_filename(document, stored_name),
+            original_filename,
+            file_data,
+            mime_type,
+            user_id,
+            deduplication_policy: DeduplicationPolicy::Skip,
+            source_type: Some(SOURCE_TYPE.to_string()),
+            source_id: None,
+            original_created_at: document.created,
+            original_modified_at: document.modified,
+            source_path: Some(stored_name.to_string()),
+            file_permissions: None,
+            file_owner: None,
+            file_group: None,
+            source_metadata: Some(json!({ "paperless": export.metadata_of(document) })),
+        };
+
+        let (document_id, created) = match self
+            .ingestion_service
+            .ingest_document(request)
+            .await
+            .map_err(|e| anyhow!("ingestion failed: {}", e))?
+        {
+            IngestionResult::Created(created) | IngestionResult::NewVersion(created) => (created.id, true),
+            IngestionResult::ExistingDocument(existing) => (existing.id, false),
+            IngestionResult::Skipped { existing_document_id, .. }
+            | IngestionResult::TrackedAsDuplicate { existing_document_id } => (existing_document_id, false),
+        };
+
+        // Applied to already present documents too, so an interrupted import is completed
+        self.apply_labels(export, document, document_id, user_id, tag_labels).await?;
+        if !created {
+            return Ok(DocumentOutcome::AlreadyPresent);
+        }
+
+        if let Some(added) = document.added {
+            self.db.set_document_created_at(document_id, added).await?;
+        }
+
+        let content = document.content.as_deref().map(str::trim).filter(|content| !content.is_empty());
+        let queued = match content {
+            Some(content) if !self.options.reocr => {
+                let word_count = content.split_whitespace().count() as i32;
+                self.db
+                    .update_document_ocr(document_id, Some(content.to_string()), None, Some(word_count), None, Some("completed".to_string()))
+                    .await?;
+                false
+            }
+            _ => {
+                self.queue_service.enqueue_document(document_id, 5, file_size).await?;
+                true
+            }
+        };
+
+        Ok(DocumentOutcome::Imported { queued })
+    }
+
+    async fn apply_labels(
+        &self,
+        export: &PaperlessExport,
+        document: &PaperlessDocument,
+        document_id: Uuid,
+        user_id: Uuid,
+        tag_labels: &HashMap<i64, Uuid>,
+    ) -> Result<()> {
+        for tag_id in &document.tags {
+            if let Some(label_id) = tag_labels.get(tag_id) {
+                self.db.assign_label(document_id, *label_id, user_id).await?;
+            }
+        }
+
+        if let Some(name) = document.correspondent.and_then(|id| export.correspondents.get(&id)) {
+            let correspondent_id = self.db.ensure_correspondent(user_id, name).await?;
+            self.db
+                .assign_correspondent(user_id, &[document_id], Some(correspondent_id), "manual")
+                .await?;
+        }
+
+        let mut extra_labels = Vec::new();
+        if self.options.correspondent_labels {
+            if let Some(name) = document.correspondent.and_then(|id| export.correspondents.get(&id)) {
+                extra_labels.push(format!("Correspondent: {}", name));
+            }
+        }
+        if self.options.document_type_labels {
+            if let Some(name) = document.document_type.and_then(|id| export.document_types.get(&id)) {
+                extra_labels.push(format!("Document type: {}", name));
+            }
+        }
+        for label in extra_labels {
+            self.db.assign_label_by_name(user_id, document_id, &label).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> Vec<ManifestRecord> {
+        serde_json::from_value(json!([
+            { "model": "documents.tag", "pk": 1, "fields": { "name": "Invoices", "color": "#A6CEE3" } },
+            { "model": "documents.correspondent", "pk": 2, "fields": { "name": "ACME Corp" } },
+            { "model": "documents.documenttype", "pk": 3, "fields": { "name": "Invoice" } },
+            { "model": "documents.customfield", "pk": 4, "fields": {
+                "name": "Status", "data_type": "select",
+                "extra_data": { "select_options": [{ "id": "abc", "label": "Paid" }, { "id": "def", "label": "Open" }] }
+            } },
+            { "model": "documents.customfield", "pk": 5, "fields": { "name": "Amount", "data_type": "monetary" } },
+            { "model": "documents.document", "pk": 10, "fields": {
+                "title": "ACME invoice 42", "content": "Invoice 42", "created": "2023-01-15",
+                "modified": "2023-01-16T10:00:00.123Z", "added": "2023-01-16T09:00:00+01:00",
+                "correspondent": 2, "document_type": 3, "tags": [1], "mime_type": "application/pdf",
+                "original_filename": "scan_0042.pdf", "archive_serial_number": 42, "storage_type": "unencrypted"
+            }, "__exported_file_name__": "0000010.pdf", "__exported_archive_name__": "0000010-archive.pdf" },
+            { "model": "documents.customfieldinstance", "pk": 20, "fields": { "document": 10, "field": 4, "value_text": null, "value_select": "def" } },
+            { "model": "documents.customfieldinstance", "pk": 21, "fields": { "document": 10, "field": 5, "value_monetary": "EUR120.00" } },
+            { "model": "documents.note", "pk": 30, "fields": { "document": 10, "note": "Paid late", "created": "2023-02-01T08:00:00Z" } },
+            { "model": "auth.user", "pk": 1, "fields": { "username": "admin" } }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_export_from_records() {
+        let export = PaperlessExport::from_records(manifest());
+        assert_eq!(export.documents.len(), 1);
+        assert_eq!(export.tags[&1].color.as_deref(), Some("#a6cee3"));
+
+        let document = &export.documents[0];
+        assert_eq!(document.created, parse_timestamp("2023-01-15T00:00:00Z"));
+        assert_eq!(document.added, parse_timestamp("2023-01-16T08:00:00Z"));
+        assert!(!document.encrypted);
+
+        let metadata = export.metadata_of(document);
+        assert_eq!(metadata["correspondent"], "ACME Corp");
+        assert_eq!(metadata["document_type"], "Invoice");
+        assert_eq!(metadata["custom_fields"]["Status"], "Open");
+        assert_eq!(metadata["custom_fields"]["Amount"], "EUR120.00");
+        assert_eq!(metadata["notes"][0]["note"], "Paid late");
+    }
+
+    #[test]
+    fn test_display_filename_and_paths() {
+        let export = PaperlessExport::from_records(manifest());
+        let mut document = export.documents[0].clone();
+        assert_eq!(display_filename(&document, "0000010.pdf"), "ACME invoice 42.pdf");
+        document.title = "a/b.PDF".to_string();
+        assert_eq!(display_filename(&document, "0000010.pdf"), "a-b.PDF");
+        document.title = String::new();
+        assert_eq!(display_filename(&document, "0000010.pdf"), "0000010.pdf");
+
+        let export_dir = Path::new("/export");
+        assert_eq!(export_path(export_dir, "originals/a.pdf"), Some(PathBuf::from("/export/originals/a.pdf")));
+        assert_eq!(export_path(export_dir, "../etc/passwd"), None);
+        assert_eq!(export_path(export_dir, "/etc/passwd"), None);
+    }
+}
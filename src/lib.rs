@@ -3,7 +3,10 @@ pub mod commands;
 pub mod config;
 pub mod db;
 pub mod db_guardrails_simple;
+pub mod embedded_metadata;
 pub mod errors;
+#[cfg(feature = "graphql")]
+pub mod graphql;
 pub mod ingestion;
 pub mod metadata_extraction;
 pub mod mime_detection;
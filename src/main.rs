@@ -4,13 +4,20 @@ use axum::{
     Router,
 };
 use std::sync::Arc;
-use tower_http::{cors::CorsLayer, services::{ServeDir, ServeFile}};
+use tower_http::{catch_panic::CatchPanicLayer, cors::CorsLayer, services::{ServeDir, ServeFile}};
 use tracing::{info, error, warn};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use anyhow;
 use sqlx::Column;
 use clap::{Parser, Subcommand};
 
-use readur::{config::Config, db::Database, AppState, *};
+use readur::{
+    config::Config,
+    db::Database,
+    models::NodeRole,
+    scheduling::{cluster, shutdown},
+    AppState, *,
+};
 
 mod commands;
 
@@ -32,6 +39,11 @@ enum Commands {
     Serve,
     /// Reset the admin user's password
     ResetAdminPassword,
+    /// Maintenance tasks, run without starting the web server
+    Admin {
+        #[command(subcommand)]
+        command: commands::AdminCommand,
+    },
 }
 
 /// Determines the correct path for static files based on the environment
@@ -90,8 +102,13 @@ async fn main() -> anyhow::Result<()> {
                 .add_directive("readur=info".parse().unwrap())                 // Keep our app logs at info
         });
 
-    tracing_subscriber::fmt()
-        .with_env_filter(env_filter)
+    // Spans are also exported over OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set; the
+    // guard flushes them on shutdown
+    let (otel_layer, _telemetry_guard) = readur::monitoring::telemetry::init();
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel_layer)
         .init();
 
     // Handle CLI commands
@@ -112,6 +129,11 @@ async fn main() -> anyhow::Result<()> {
 
             return Ok(());
         }
+        Some(Commands::Admin { command }) => {
+            let config = Config::from_env()?;
+            commands::run_admin_command(command, &config).await?;
+            return Ok(());
+        }
         Some(Commands::Serve) | None => {
             // Default: Start the web server
             // Continue with normal server startup below
@@ -135,10 +157,27 @@ async fn main() -> anyhow::Result<()> {
         }
     };
     
+    // Which work this process takes on, from NODE_NAME and NODE_ROLES
+    let node = match cluster::NodeConfig::from_env() {
+        Ok(node) => cluster::init(node),
+        Err(e) => {
+            println!("❌ CRITICAL: {}", e);
+            return Err(anyhow::anyhow!(e));
+        }
+    };
+
+    // SIGTERM and Ctrl-C stop new jobs; running ones get SHUTDOWN_GRACE_PERIOD_SECONDS
+    shutdown::listen();
+
     // Log critical configuration values that affect startup
     println!("\n🔗 STARTUP CONFIGURATION:");
     println!("{}", "=".repeat(50));
     println!("🌐 Server will start on: {}", config.server_address);
+    println!(
+        "🧩 Node: {} (roles: {})",
+        node.name,
+        node.roles.iter().map(|role| role.to_string()).collect::<Vec<_>>().join(", ")
+    );
     // Parse database URL safely without exposing credentials
     let db_info = if let Some(at_pos) = config.database_url.find('@') {
         let host_part = &config.database_url[at_pos + 1..];
@@ -204,6 +243,15 @@ async fn main() -> anyhow::Result<()> {
         return Err(e.into());
     }
     info!("✅ Storage backend initialized successfully");
+
+    // Files stored before a change of backend stay readable from where they are
+    let file_service = match readur::storage::factory::create_read_backends(&config, file_service.storage_type()).await {
+        Ok(read_backends) => file_service.with_read_backends(read_backends),
+        Err(e) => {
+            warn!("Failed to set up additional storage backends for reads: {}", e);
+            file_service
+        }
+    };
     
     // Wrap file service in Arc for sharing across application state
     let file_service = std::sync::Arc::new(file_service);
@@ -248,6 +296,22 @@ async fn main() -> anyhow::Result<()> {
         }
     };
     
+    // Heavy reads of the web pool go to a replica when one is configured; the
+    // background pool keeps the queue on the primary
+    let web_db = match std::env::var("DATABASE_REPLICA_URL").ok().filter(|url| !url.trim().is_empty()) {
+        Some(replica_url) => match web_db.clone().with_read_replica(&replica_url, 20) {
+            Ok(db) => {
+                println!("✅ Read replica configured for search, listing and statistics queries");
+                db
+            }
+            Err(e) => {
+                println!("⚠️  Invalid DATABASE_REPLICA_URL, reading from the primary: {}", e);
+                web_db
+            }
+        },
+        None => web_db,
+    };
+    
     // Don't run the old migration system - let SQLx handle everything
     // db.migrate().await?;
     
@@ -361,20 +425,36 @@ async fn main() -> anyhow::Result<()> {
     
     // Seed admin user  
     seed::seed_admin_user(&background_db).await?;
+
+    // Other nodes see this one from now on, and an earlier run of it counts as stopped
+    cluster::register(&background_db).await?;
+    let cluster_locks = cluster::ClusterLocks::new(&config.database_url);
     
-    // Reset any running WebDAV syncs from previous server instance using background DB
-    match background_db.reset_running_webdav_syncs().await {
-        Ok(count) => {
-            if count > 0 {
-                info!("Reset {} orphaned WebDAV sync states from server restart", count);
-            }
-        }
+    // Reset any running WebDAV syncs from previous server instance using background DB.
+    // WebDAV sync states do not record their node, so they are left alone while another
+    // sync node is online.
+    let other_sync_node = match background_db.other_live_node_has_role(node.id, &NodeRole::Sync.to_string()).await {
+        Ok(online) => online,
         Err(e) => {
-            warn!("Failed to reset running WebDAV syncs: {}", e);
+            warn!("Failed to look for other sync nodes: {}", e);
+            false
+        }
+    };
+    if !other_sync_node {
+        match background_db.reset_running_webdav_syncs().await {
+            Ok(count) => {
+                if count > 0 {
+                    info!("Reset {} orphaned WebDAV sync states from server restart", count);
+                }
+            }
+            Err(e) => {
+                warn!("Failed to reset running WebDAV syncs: {}", e);
+            }
         }
     }
     
-    // Reset any running universal source syncs from previous server instance
+    // Reset any running universal source syncs from previous server instance; those of
+    // sync nodes still online are left to them
     match background_db.reset_running_source_syncs().await {
         Ok(count) => {
             if count > 0 {
@@ -386,8 +466,59 @@ async fn main() -> anyhow::Result<()> {
         }
     }
     
+    // Per-workspace and per-user encryption of stored files and source credentials,
+    // enabled by ENCRYPTION_MASTER_KEY
+    let file_service = match readur::services::encryption::EncryptionService::from_env(background_db.clone()) {
+        Ok(Some(encryption)) => {
+            println!("🔐 File encryption enabled ({})", encryption.describe());
+            let encryption = Arc::new(encryption);
+            readur::services::encryption::install(encryption.clone());
+            Arc::new((*file_service).clone().with_encryption(encryption))
+        }
+        Ok(None) => {
+            println!("ℹ️  File encryption is disabled");
+            file_service
+        }
+        Err(e) => {
+            error!("❌ Invalid encryption configuration: {}", e);
+            return Err(e.into());
+        }
+    };
+
+    // Shared content blobs, stored for identical files when STORAGE_DEDUPLICATION is on
+    let deduplicate = readur::services::file_service::deduplication_enabled();
+    if deduplicate {
+        if file_service.encryption().is_some() {
+            warn!("STORAGE_DEDUPLICATION has no effect while file encryption is enabled");
+        } else {
+            println!("🧬 Storage deduplication enabled");
+        }
+    }
+    let file_service = Arc::new((*file_service).clone().with_blob_store(background_db.clone(), deduplicate));
+
+    // Incoming files are scanned by clamd before they are stored, when CLAMAV_ADDRESS is set
+    match readur::services::malware_scan_service::configured() {
+        Ok(Some(scanner)) => {
+            println!("🛡️  Malware scanning enabled ({})", scanner.describe());
+            if let Err(e) = scanner.ping().await {
+                warn!("clamd is not answering yet: {}", e);
+            }
+        }
+        Ok(None) => println!("ℹ️  Malware scanning is disabled"),
+        Err(e) => error!("❌ Invalid malware scanning configuration, ingestion will fail: {}", e),
+    }
+
+    // Identifying metadata (EXIF, PDF authors, XMP) is stripped per STRIP_EMBEDDED_METADATA
+    match readur::embedded_metadata::configured() {
+        Ok(readur::embedded_metadata::StripPolicy::Never) => {}
+        Ok(readur::embedded_metadata::StripPolicy::Shared) => println!("🕶️  Embedded metadata is stripped from shared copies"),
+        Ok(readur::embedded_metadata::StripPolicy::Always) => println!("🕶️  Embedded metadata is stripped before files are stored"),
+        Err(e) => error!("❌ {}, ingestion and sharing will fail", e),
+    }
+
     // Create shared OCR queue service for both web and background operations
-    let concurrent_jobs = 15; // Limit concurrent OCR jobs to prevent DB pool exhaustion
+    // Capped at MAX_CONCURRENT_OCR_JOBS to prevent DB pool exhaustion; adjustable at runtime via /api/queue/workers
+    let concurrent_jobs = config.concurrent_ocr_jobs;
     let shared_queue_service = Arc::new(readur::ocr::queue::OcrQueueService::new(
         background_db.clone(), 
         background_db.get_pool().clone(), 
@@ -462,14 +593,23 @@ async fn main() -> anyhow::Result<()> {
     };
     let background_state = Arc::new(background_state);
     
-    let watcher_config = config.clone();
-    let watcher_db = background_state.db.clone();
-    let watcher_file_service = background_state.file_service.clone();
-    tokio::spawn(async move {
-        if let Err(e) = readur::scheduling::watcher::start_folder_watcher(watcher_config, watcher_db, watcher_file_service).await {
-            error!("Folder watcher error: {}", e);
-        }
-    });
+    // One sync node at a time watches the watch folder
+    if node.runs(NodeRole::Sync) {
+        let watcher_config = config.clone();
+        let watcher_db = background_state.db.clone();
+        let watcher_file_service = background_state.file_service.clone();
+        readur::errors::panic::spawn_guarded(
+            "Folder watcher",
+            cluster_locks.clone().run_exclusive("folder_watcher", move || {
+                let (config, db, file_service) = (watcher_config.clone(), watcher_db.clone(), watcher_file_service.clone());
+                async move {
+                    if let Err(e) = readur::scheduling::watcher::start_folder_watcher(config, db, file_service).await {
+                        error!("Folder watcher error: {}", e);
+                    }
+                }
+            }),
+        );
+    }
     
     // Create dedicated runtime for OCR processing to prevent interference with WebDAV
     let ocr_runtime = tokio::runtime::Builder::new_multi_thread()
@@ -492,35 +632,323 @@ async fn main() -> anyhow::Result<()> {
         .enable_all()
         .build()?;
     
-    // Start OCR queue worker on dedicated OCR runtime using shared queue service
-    let queue_worker = shared_queue_service.clone();
-    ocr_runtime.spawn(async move {
-        info!("🚀 Starting OCR queue worker...");
-        if let Err(e) = queue_worker.start_worker().await {
-            error!("❌ OCR queue worker error: {}", e);
-        } else {
-            info!("✅ OCR queue worker started successfully");
-        }
-    });
+    // Report this node to the others, with its OCR load and the exclusive tasks it runs
+    let heartbeat_queue = node.runs(NodeRole::Ocr).then(|| shared_queue_service.clone());
+    background_runtime.spawn(cluster::run_heartbeat(background_state.db.clone(), cluster_locks.clone(), heartbeat_queue));
+
+    // OCR nodes share the queue, each claiming the items it works on
+    if node.runs(NodeRole::Ocr) {
+        // Start OCR queue worker on dedicated OCR runtime using shared queue service
+        let queue_worker = shared_queue_service.clone();
+        ocr_runtime.spawn(async move {
+            info!("🚀 Starting OCR queue worker...");
+            if let Err(e) = queue_worker.start_worker().await {
+                error!("❌ OCR queue worker error: {}", e);
+            } else {
+                info!("✅ OCR queue worker started successfully");
+            }
+        });
     
-    // Start OCR maintenance tasks on dedicated OCR runtime
-    let queue_maintenance = shared_queue_service.clone();
-    ocr_runtime.spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(300)); // Every 5 minutes
-        loop {
-            interval.tick().await;
+        // Start OCR maintenance tasks on dedicated OCR runtime
+        let queue_maintenance = shared_queue_service.clone();
+        ocr_runtime.spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(300)); // Every 5 minutes
+            loop {
+                interval.tick().await;
             
-            // Recover stale items (older than 10 minutes)
-            if let Err(e) = queue_maintenance.recover_stale_items(10).await {
-                error!("Error recovering stale items: {}", e);
+                // A panic skips this round instead of ending maintenance for good
+                let _ = readur::errors::panic::catch_panic("OCR queue maintenance", async {
+                    // Recover stale items (older than 10 minutes)
+                    if let Err(e) = queue_maintenance.recover_stale_items(10).await {
+                        error!("Error recovering stale items: {}", e);
+                    }
+                
+                    // Clean up old completed items (older than 7 days)
+                    if let Err(e) = queue_maintenance.cleanup_completed(7).await {
+                        error!("Error cleaning up completed items: {}", e);
+                    }
+                }).await;
             }
-            
-            // Clean up old completed items (older than 7 days)
-            if let Err(e) = queue_maintenance.cleanup_completed(7).await {
-                error!("Error cleaning up completed items: {}", e);
+        });
+    }
+
+    // Optional startup consistency check between the database and storage
+    let consistency_mode = readur::services::consistency_service::ConsistencyCheckMode::from_env();
+    if node.runs(NodeRole::Maintenance) && consistency_mode != readur::services::consistency_service::ConsistencyCheckMode::Off {
+        let consistency_service = readur::services::consistency_service::ConsistencyService::new(
+            background_state.db.clone(),
+            background_state.file_service.as_ref().clone(),
+        );
+        let repair = consistency_mode == readur::services::consistency_service::ConsistencyCheckMode::Repair;
+        println!("🔍 Running startup consistency check in the background (repair: {})", repair);
+        background_runtime.spawn(async move {
+            if let Err(e) = consistency_service.run(repair, false).await {
+                error!("Startup consistency check failed: {}", e);
             }
+        });
+    }
+
+    // Scheduled consistency check that notifies admins of new issues
+    let consistency_schedule = readur::services::consistency_service::ConsistencySchedule::from_env()
+        .filter(|_| node.runs(NodeRole::Maintenance));
+    if let Some(schedule) = consistency_schedule {
+        println!("🔍 Consistency check scheduled every {} hours (verify hashes: {})", schedule.interval_hours, schedule.verify_hashes);
+        let consistency_state = background_state.clone();
+        background_runtime.spawn(cluster_locks.clone().run_exclusive("consistency_check", move || {
+            readur::services::consistency_service::ConsistencyService::new(
+                consistency_state.db.clone(),
+                consistency_state.file_service.as_ref().clone(),
+            )
+            .run_scheduled(schedule)
+        }));
+    }
+    
+    // Jobs the last shutdown interrupted are resumed by a maintenance node starting while
+    // no other is online, as one that is may still be running them
+    let other_maintenance_node = match background_state
+        .db
+        .other_live_node_has_role(node.id, &NodeRole::Maintenance.to_string())
+        .await
+    {
+        Ok(online) => online,
+        Err(e) => {
+            warn!("Failed to look for other maintenance nodes: {}", e);
+            false
         }
-    });
+    };
+    if node.runs(NodeRole::Maintenance) && !other_maintenance_node {
+        // Continue a storage migration that was interrupted by the last shutdown
+        let storage_migration_service = readur::services::storage_migration_service::StorageMigrationService::new(
+            background_state.db.clone(),
+            background_state.file_service.as_ref().clone(),
+            background_state.config.clone(),
+        );
+        background_runtime.spawn(async move {
+            if let Err(e) = storage_migration_service.resume_interrupted().await {
+                error!("Failed to resume storage migration: {}", e);
+            }
+        });
+
+        // Continue moving files into the storage layout where the last shutdown stopped
+        let storage_relocation_service = readur::services::storage_relocation_service::StorageRelocationService::new(
+            background_state.db.clone(),
+            background_state.file_service.as_ref().clone(),
+        );
+        background_runtime.spawn(async move {
+            if let Err(e) = storage_relocation_service.resume_interrupted().await {
+                error!("Failed to resume storage relocation: {}", e);
+            }
+        });
+
+        // Continue bulk document operations that were interrupted by the last shutdown
+        let bulk_operation_service = readur::services::bulk_operation_service::BulkOperationService::new(background_state.clone());
+        background_runtime.spawn(async move {
+            if let Err(e) = bulk_operation_service.resume_interrupted().await {
+                error!("Failed to resume bulk operations: {}", e);
+            }
+        });
+
+        // Continue OCR engine comparisons that were interrupted by the last shutdown
+        let ocr_comparison_service = readur::services::ocr_comparison_service::OcrComparisonService::new(
+            background_state.db.clone(),
+            (*background_state.file_service).clone(),
+        );
+        background_runtime.spawn(async move {
+            if let Err(e) = ocr_comparison_service.resume_interrupted().await {
+                error!("Failed to resume OCR comparisons: {}", e);
+            }
+        });
+
+        // Continue a search index rebuild that was interrupted by the last shutdown
+        let search_index_service = readur::services::search_index_service::SearchIndexService::new(background_state.db.clone());
+        background_runtime.spawn(async move {
+            if let Err(e) = search_index_service.resume_interrupted().await {
+                error!("Failed to resume search index rebuild: {}", e);
+            }
+        });
+
+        // Write library exports again that were interrupted by the last shutdown
+        let export_service = readur::services::export_service::ExportService::new(
+            background_state.db.clone(),
+            background_state.file_service.as_ref().clone(),
+        );
+        background_runtime.spawn(async move {
+            if let Err(e) = export_service.resume_interrupted().await {
+                error!("Failed to resume exports: {}", e);
+            }
+        });
+
+        // Restore export archives again that were interrupted by the last shutdown
+        let import_service = readur::services::import_service::ImportService::new(background_state.clone());
+        background_runtime.spawn(async move {
+            if let Err(e) = import_service.resume_interrupted().await {
+                error!("Failed to resume imports: {}", e);
+            }
+        });
+
+        // Narrate documents again whose narration was interrupted by the last shutdown
+        let narration_service = readur::services::narration_service::NarrationService::new(
+            background_state.db.clone(),
+            background_state.file_service.as_ref().clone(),
+        );
+        background_runtime.spawn(async move {
+            if let Err(e) = narration_service.resume_interrupted().await {
+                error!("Failed to resume narrations: {}", e);
+            }
+        });
+    }
+
+    // Run analysis batches, taking over those of nodes that stopped
+    if node.runs(NodeRole::Analysis) {
+        let analysis_batch_service = readur::services::analysis_batch_service::AnalysisBatchService::new(background_state.db.clone());
+        background_runtime.spawn(analysis_batch_service.run_worker(node.id));
+    }
+
+    if node.runs(NodeRole::Sync) {
+        // Ingest S3 objects from bucket notification queues as they arrive
+        let s3_event_state = background_state.clone();
+        background_runtime.spawn(cluster_locks.clone().run_exclusive("s3_events", move || {
+            readur::services::s3_event_service::S3EventService::new(s3_event_state.clone()).run_queue_listeners()
+        }));
+
+        // Ingest files dropped into the folders of local folder sources as they arrive
+        let local_folder_watch_state = background_state.clone();
+        background_runtime.spawn(cluster_locks.clone().run_exclusive("local_folder_watch", move || {
+            readur::services::local_folder_watch_service::LocalFolderWatchService::new(local_folder_watch_state.clone()).run()
+        }));
+    }
+
+    if node.runs(NodeRole::Maintenance) {
+        // Retry webhook deliveries that failed
+        let webhook_retries = readur::services::event_service::EventService::new(background_state.db.clone());
+        background_runtime.spawn(webhook_retries.run_retries());
+
+        // Remove stored events past their retention
+        let event_cleanup_db = background_state.db.clone();
+        background_runtime.spawn(cluster_locks.clone().run_exclusive("event_log_cleanup", move || {
+            readur::services::event_service::EventService::new(event_cleanup_db.clone()).run_cleanup()
+        }));
+
+        // Send notifications on to the email, ntfy, Gotify and Slack channels of their users
+        let notification_service = readur::services::notification_service::NotificationService::new(background_state.db.clone());
+        background_runtime.spawn(notification_service.run());
+
+        // Turn due document reminders into notifications
+        let reminder_service = readur::services::reminder_service::ReminderService::new(background_state.db.clone());
+        background_runtime.spawn(reminder_service.run());
+
+        // Notify owners of documents about to expire or renew
+        let expiration_service = readur::services::expiration_service::ExpirationService::new(background_state.db.clone());
+        background_runtime.spawn(expiration_service.run());
+
+        // Email daily and weekly digests to the users who asked for them
+        let digest_db = background_state.db.clone();
+        background_runtime.spawn(cluster_locks.clone().run_exclusive("digests", move || {
+            readur::services::digest_service::DigestService::new(digest_db.clone()).run()
+        }));
+
+        // Recompress oversized scans under the storage compression policies, and delete
+        // the originals kept alongside once their window ends
+        let storage_compression_state = background_state.clone();
+        background_runtime.spawn(cluster_locks.clone().run_exclusive("storage_compression", move || {
+            readur::services::storage_compression_service::StorageCompressionService::new(
+                storage_compression_state.db.clone(),
+                storage_compression_state.file_service.as_ref().clone(),
+            )
+            .run()
+        }));
+    }
+
+    if node.runs(NodeRole::Api) {
+        // Stream OCR queue progress to clients connected to this node
+        let queue_progress = readur::services::event_service::EventService::new(background_state.db.clone());
+        background_runtime.spawn(queue_progress.run_queue_progress());
+
+        // Move reads back to the replica once it is reachable and has caught up
+        background_runtime.spawn(web_state.db.clone().run_replica_health_check());
+    }
+
+    // Render thumbnails of new documents ahead of their first view, behind OCR
+    if node.runs(NodeRole::Ocr) {
+        let thumbnail_service = readur::services::thumbnail_service::ThumbnailService::new(
+            background_state.db.clone(),
+            background_state.file_service.as_ref().clone(),
+        );
+        background_runtime.spawn(thumbnail_service.run());
+    }
+
+    // Read the data residency policy now, so a restrictive one is logged at startup
+    readur::services::data_residency::policy();
+
+    // Outbound request limits admins set, here and on other instances
+    background_runtime.spawn(readur::services::outbound_limits::run_refresh(background_state.db.clone()));
+
+    if node.runs(NodeRole::Maintenance) {
+        // Keep the external search engine index in step with the event log
+        if let Some(external_search) = readur::services::external_search::configured() {
+            info!(
+                "External search enabled: {} index '{}'",
+                external_search.config.engine.as_str(),
+                external_search.config.index
+            );
+            let indexer_db = background_state.db.clone();
+            background_runtime.spawn(cluster_locks.clone().run_exclusive("external_search_indexer", move || {
+                readur::services::external_search::indexer::ExternalSearchIndexer::new(
+                    indexer_db.clone(),
+                    external_search.clone(),
+                )
+                .run()
+            }));
+        }
+
+        // Archive and delete documents past their retention policies
+        let retention_state = background_state.clone();
+        background_runtime.spawn(cluster_locks.clone().run_exclusive("retention", move || {
+            readur::services::retention_service::RetentionService::new(retention_state.clone()).run()
+        }));
+
+        // Remove resumable uploads abandoned past their expiry
+        let upload_state = background_state.clone();
+        background_runtime.spawn(cluster_locks.clone().run_exclusive("upload_cleanup", move || {
+            readur::services::upload_service::UploadService::new(upload_state.clone()).run()
+        }));
+
+        // Remove LLM debug log runs past their retention
+        let debug_log_db = background_state.db.clone();
+        background_runtime.spawn(cluster_locks.clone().run_exclusive("llm_debug_log_cleanup", move || {
+            readur::services::llm::debug_log::run_cleanup(debug_log_db.clone())
+        }));
+
+        // Back up the library to S3 or WebDAV on a schedule
+        match readur::services::backup_service::configured() {
+            Ok(Some(config)) => {
+                info!(
+                    "Backups enabled: {} backups to {} every {} hour(s)",
+                    config.mode.as_str(),
+                    config.target.describe(),
+                    config.interval_hours
+                );
+                let backup_state = background_state.clone();
+                background_runtime.spawn(cluster_locks.clone().run_exclusive("backups", move || {
+                    readur::services::backup_service::BackupService::new(backup_state.clone(), config.clone()).run()
+                }));
+            }
+            Ok(None) => {}
+            Err(e) => error!("Backups disabled: {}", e),
+        }
+    }
+
+    // Every sync node accepts scans of its own
+    if node.runs(NodeRole::Sync) {
+        match readur::services::ftp_drop_service::configured() {
+            Ok(Some(config)) => {
+                let ftp_drop = readur::services::ftp_drop_service::FtpDropService::new(background_state.clone(), config);
+                background_runtime.spawn(ftp_drop.run());
+            }
+            Ok(None) => {}
+            Err(e) => error!("FTP scan drop disabled: {}", e),
+        }
+    }
     
     // Create universal source scheduler with background state (handles WebDAV, Local, S3)
     println!("\n📅 SCHEDULER INITIALIZATION:");
@@ -548,16 +976,19 @@ async fn main() -> anyhow::Result<()> {
     };
     let web_state = Arc::new(updated_web_state);
     
-    // Start universal source scheduler on background runtime
-    println!("⏰ Scheduling background source sync to start in 30 seconds");
-    let scheduler_for_background = source_scheduler.clone();
-    background_runtime.spawn(async move {
-        info!("Starting universal source sync scheduler with 30-second startup delay");
-        // Wait 30 seconds before starting scheduler to allow server to fully initialize
-        tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-        info!("🔄 Universal source sync scheduler starting after startup delay - this will check for WebDAV sources!");
-        scheduler_for_background.start().await;
-    });
+    // Start universal source scheduler on background runtime. Sync nodes share the
+    // schedule, each claiming the sources it syncs.
+    if node.runs(NodeRole::Sync) {
+        println!("⏰ Scheduling background source sync to start in 30 seconds");
+        let scheduler_for_background = source_scheduler.clone();
+        background_runtime.spawn(async move {
+            info!("Starting universal source sync scheduler with 30-second startup delay");
+            // Wait 30 seconds before starting scheduler to allow server to fully initialize
+            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+            info!("🔄 Universal source sync scheduler starting after startup delay - this will check for WebDAV sources!");
+            scheduler_for_background.start().await;
+        });
+    }
     
     // Determine the correct static files path for SPA serving
     let static_dir = determine_static_files_path();
@@ -569,34 +1000,122 @@ async fn main() -> anyhow::Result<()> {
     // Create the router with the updated state
     let app = Router::new()
         .route("/api/health", get(readur::health_check))
+        .nest("/health", readur::routes::health::router())
+        .nest("/api/analysis", readur::routes::analysis::router())
+        .nest("/api/audit", readur::routes::audit::router())
         .nest("/api/auth", readur::routes::auth::router())
+        .nest("/api/backups", readur::routes::backups::router())
+        .nest("/api/client-sync", readur::routes::client_sync::router())
+        .nest("/api/cluster", readur::routes::cluster::router())
+        .nest("/api/collections", readur::routes::collections::router())
+        .nest("/api/consistency", readur::routes::consistency::router())
+        .nest("/api/correspondents", readur::routes::correspondents::router())
+        .nest("/api/document-types", readur::routes::document_types::router())
         .nest("/api/documents", readur::routes::documents::router())
+        .nest("/api/embargoes", readur::routes::embargoes::router())
+        .nest("/api/encryption", readur::routes::encryption::router())
+        .nest("/api/entities", readur::routes::entities::router())
+        .nest("/api/events", readur::routes::events::router())
+        .nest("/api/export", readur::routes::export::router())
+        .nest("/api/groups", readur::routes::groups::router())
+        .nest("/api/guest-portals", readur::routes::guest_portals::router())
         .nest("/api/ignored-files", readur::routes::ignored_files::ignored_files_routes())
+        .nest("/api/i18n", readur::routes::i18n::router())
+        .nest("/api/import", readur::routes::import::router())
+        .nest("/api/ingestion-hooks", readur::routes::ingestion_hooks::router())
+        .nest("/api/instance", readur::routes::instance::router())
+        .nest("/api/invoices", readur::routes::invoices::router())
         .nest("/api/labels", readur::routes::labels::router())
+        .nest("/api/legal-holds", readur::routes::legal_holds::router())
         .nest("/api/metrics", readur::routes::metrics::router())
         .nest("/metrics", readur::routes::prometheus_metrics::router())
         .nest("/api/llm", readur::routes::llm::router())
+        .nest("/api/maintenance", readur::routes::maintenance::router())
         .nest("/api/notifications", readur::routes::notifications::router())
         .nest("/api/ocr", readur::routes::ocr::router())
+        .nest("/api/ocr/comparisons", readur::routes::ocr_comparisons::router())
+        .nest("/api/omr", readur::routes::omr::router())
+        .nest("/api/outbound-limits", readur::routes::outbound_limits::router())
+        .nest("/api/data-residency", readur::routes::data_residency::router())
+        .nest("/api/physical-locations", readur::routes::physical_locations::router())
+        .nest("/api/public/calendar", readur::routes::calendar::public_router())
+        .nest("/api/public/portals", readur::routes::guest_portals::public_router())
+        .nest("/api/public/shares", readur::routes::share_links::public_router())
+        .nest("/api/quarantine", readur::routes::quarantine::router())
         .nest("/api/queue", readur::routes::queue::router())
+        .nest("/api/reminders", readur::routes::reminders::router())
+        .nest("/api/expirations", readur::routes::expirations::router())
+        .nest("/api/retention", readur::routes::retention::router())
+        .nest("/api/saved_searches", readur::routes::saved_searches::router())
+        .nest("/api/scan-bridges", readur::routes::scan_bridges::router())
+        .nest("/api/scan-devices", readur::routes::scan_devices::router())
         .nest("/api/search", readur::routes::search::router())
         .nest("/api/settings", readur::routes::settings::router())
+        .nest("/api/share-links", readur::routes::share_links::router())
+        .nest("/api/shares", readur::routes::shares::router())
         .nest("/api/source/errors", readur::routes::source_errors::router())
         .nest("/api/sources", readur::routes::sources::router())
+        .nest("/api/storage/compression", readur::routes::storage_compression::router())
+        .nest("/api/storage/layout", readur::routes::storage_layout::router())
+        .nest("/api/storage/migrations", readur::routes::storage_migrations::router())
+        .nest("/api/timeline", readur::routes::timeline::router())
+        .nest("/api/statistics", readur::routes::statistics::router())
+        .nest("/api/uploads", readur::routes::uploads::router())
         .nest("/api/users", readur::routes::users::router())
         .nest("/api/webdav", readur::routes::webdav::router())
         .nest("/api/webdav/scan/failures", readur::routes::webdav_scan_failures::router())
-        .merge(readur::swagger::create_swagger_router())
+        .nest("/api/webhooks", readur::routes::webhooks::router())
+        .nest("/api/workflow", readur::routes::workflow::router())
+        .nest("/api/workspaces", readur::routes::workspaces::router())
+        .merge(readur::routes::library_dav::router())
+        .merge(readur::swagger::create_swagger_router());
+    #[cfg(feature = "graphql")]
+    let app = app.nest("/api/graphql", readur::routes::graphql::router());
+    let app = app
         .fallback_service(
             ServeDir::new(&static_dir)
                 .precompressed_gzip()
                 .precompressed_br()
                 .fallback(ServeFile::new(&index_file))
         )
+        .layer(axum::middleware::from_fn(readur::services::i18n::locale_middleware))
+        .layer(axum::middleware::from_fn(readur::services::maintenance_service::maintenance_middleware))
+        .layer(axum::middleware::from_fn_with_state(
+            web_state.clone(),
+            readur::services::rate_limit_service::rate_limit_middleware,
+        ))
+        .layer(axum::middleware::from_fn(readur::monitoring::prometheus::track_http_metrics))
+        .layer(axum::middleware::from_fn(readur::monitoring::telemetry::trace_http_requests))
         .layer(DefaultBodyLimit::max(config.max_file_size_mb as usize * 1024 * 1024))
         .layer(CorsLayer::permissive())
+        .layer(CatchPanicLayer::custom(readur::errors::panic::panic_response))
         .with_state(web_state.clone());
 
+    // Nodes without the api role only answer health checks and metrics scrapes
+    let app = if node.runs(NodeRole::Api) {
+        app
+    } else {
+        println!("ℹ️  This node does not run the api role, serving health checks and metrics only");
+        Router::new()
+            .route("/api/health", get(readur::health_check))
+            .nest("/health", readur::routes::health::router())
+            .nest("/metrics", readur::routes::prometheus_metrics::router())
+            .with_state(web_state.clone())
+    };
+
+    // Read the translation catalogs now, so broken files are reported at startup
+    readur::services::i18n::reload_catalogs();
+    match web_state.db.get_instance_settings().await {
+        Ok(settings) => readur::services::i18n::set_instance_locale(settings.default_language),
+        Err(e) => warn!("Failed to load instance settings, using DEFAULT_LOCALE: {}", e),
+    }
+    // Paused queues and the maintenance mode may be changed on any node
+    readur::services::maintenance_service::refresh(&web_state.db).await;
+    tokio::spawn(readur::services::maintenance_service::run_refresh(web_state.db.clone()));
+    // Converters may be killed on any node; each keeps the quarantine they share
+    readur::services::converter_sandbox::sync(&web_state.db).await;
+    tokio::spawn(readur::services::converter_sandbox::run_sync(web_state.db.clone()));
+
     println!("\n🌐 STARTING HTTP SERVER:");
     println!("{}", "=".repeat(50));
     
@@ -627,8 +1146,46 @@ async fn main() -> anyhow::Result<()> {
     
     info!("🚀 Readur server is now running and accepting connections");
     
-    axum::serve(listener, app).await?;
-    
+    // Peer addresses are recorded in the audit log. The server stops taking connections
+    // once shutdown is requested; open event streams are cut at the end of the grace period.
+    let mut server = tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .with_graceful_shutdown(shutdown::wait())
+            .await
+    });
+    tokio::select! {
+        result = &mut server => {
+            result??;
+            return Ok(());
+        }
+        _ = shutdown::wait() => {}
+    }
+
+    let deadline = tokio::time::Instant::now() + shutdown::grace_period();
+    let running = shutdown::drain(deadline).await;
+    if running > 0 {
+        warn!("{} jobs still running at the end of the grace period, returning them to the queue", running);
+    }
+    if node.runs(NodeRole::Ocr) {
+        match shared_queue_service.release_claimed_items().await {
+            Ok(released) if released > 0 => info!("Returned {} OCR jobs to the queue", released),
+            Ok(_) => {}
+            Err(e) => error!("Failed to return OCR jobs to the queue: {}", e),
+        }
+    }
+    if let Err(e) = web_state.db.mark_worker_node_stopped(node.id).await {
+        error!("Failed to mark this node stopped: {}", e);
+    }
+    if tokio::time::timeout_at(deadline, server).await.is_err() {
+        warn!("Closed connections still open at the end of the grace period");
+    }
+
+    // Jobs left running were handed back above; dropping the runtimes here would block
+    ocr_runtime.shutdown_background();
+    background_runtime.shutdown_background();
+    db_runtime.shutdown_background();
+    info!("Readur stopped");
+
     Ok(())
 }
 
@@ -27,6 +27,16 @@ pub async fn extract_content_metadata(file_data: &[u8], mime_type: &str, filenam
             }
         }
         
+        // Emails - envelope headers (from, to, subject, date)
+        mime if crate::ocr::email_parser::is_email(mime, filename) => {
+            match crate::ocr::email_parser::load_email(file_data, mime, filename).await {
+                Ok(email) => metadata.extend(email.metadata()),
+                Err(_) => {
+                    metadata.insert("file_type".to_string(), Value::String(mime_type.to_string()));
+                }
+            }
+        }
+        
         _ => {
             // For other file types, add basic file information
             metadata.insert("file_type".to_string(), Value::String(mime_type.to_string()));
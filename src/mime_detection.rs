@@ -8,9 +8,71 @@
 /// The goal is to provide accurate MIME type detection that's particularly important
 /// for OCR processing where incorrectly classified image files can cause issues.
 
+use once_cell::sync::Lazy;
 use std::path::Path;
 use tracing::{debug, warn};
 
+/// Bytes looked at to tell text from binary content
+const TEXT_SAMPLE_SIZE: usize = 8192;
+
+/// Text formats a text file may claim to be. Any other text, such as HTML, is stored
+/// as `text/plain` so it is never served as active content.
+const TEXT_MIME_TYPES: &[&str] = &[
+    "text/plain",
+    "text/csv",
+    "text/markdown",
+    "text/tab-separated-values",
+    "text/xml",
+    "application/xml",
+    "application/json",
+    "message/rfc822",
+];
+
+/// Formats stored in a ZIP container, which magic bytes do not always tell apart
+const ZIP_BASED_MIME_TYPES: &[&str] = &[
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    "application/vnd.oasis.opendocument.text",
+    "application/vnd.oasis.opendocument.spreadsheet",
+    "application/vnd.oasis.opendocument.presentation",
+    "application/epub+zip",
+];
+
+/// Formats stored in an OLE compound file, which magic bytes do not reliably tell apart
+const OLE_BASED_MIME_TYPES: &[&str] = &[
+    "application/x-ole-storage",
+    "application/msword",
+    "application/vnd.ms-excel",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.ms-outlook",
+];
+
+/// Types accepted when `ALLOWED_MIME_TYPES` is not set
+const DEFAULT_ALLOWED_MIME_TYPES: &[&str] = &[
+    "application/pdf",
+    "image/*",
+    "text/plain",
+    "text/csv",
+    "text/markdown",
+    "text/tab-separated-values",
+    "text/xml",
+    "application/xml",
+    "application/json",
+    "application/rtf",
+    "text/rtf",
+    "message/rfc822",
+    "application/vnd.ms-outlook",
+    "application/msword",
+    "application/vnd.ms-excel",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.openxmlformats-officedocument.*",
+    "application/vnd.oasis.opendocument.*",
+];
+
+static ALLOWED_MIME_TYPES: Lazy<Vec<String>> =
+    Lazy::new(|| parse_allowlist(std::env::var("ALLOWED_MIME_TYPES").ok().as_deref()));
+
 /// Strategy for MIME type detection
 #[derive(Debug, Clone, PartialEq)]
 pub enum DetectionStrategy {
@@ -268,6 +330,173 @@ pub fn update_mime_type_with_content(
     }
 }
 
+/// Detect the type of an incoming file from its content. The claimed type and the
+/// extension only choose between formats the content cannot tell apart: documents in
+/// the same container format, or kinds of text. Binary content of no known format
+/// is `application/octet-stream`, whatever it claims to be.
+pub fn detect_upload_mime(
+    content: &[u8],
+    filename: &str,
+    claimed_mime_type: Option<&str>,
+) -> MimeDetectionResult {
+    let claimed = claimed_mime_type.map(essence).filter(|t| is_trusted_server_mime_type(t));
+    let candidates: Vec<String> = claimed
+        .iter()
+        .cloned()
+        .chain(mime_guess::from_path(filename).first().map(|m| m.essence_str().to_string()))
+        .collect();
+    let original_server_type = claimed_mime_type.map(|t| t.to_string());
+
+    // Text signatures such as HTML or shell scripts are judged as text below
+    let detected = infer::get(content).filter(|t| t.matcher_type() != infer::MatcherType::Text);
+    let mut result = if let Some(detected) = detected {
+        let detected = detected.mime_type();
+        let mime_type = candidates
+            .iter()
+            .find(|candidate| refines(candidate, detected))
+            .cloned()
+            .unwrap_or_else(|| detected.to_string());
+        MimeDetectionResult::from_content(mime_type, original_server_type)
+    } else if looks_like_text(content) {
+        let mime_type = candidates
+            .iter()
+            .find(|candidate| TEXT_MIME_TYPES.contains(&candidate.as_str()))
+            .cloned()
+            .unwrap_or_else(|| "text/plain".to_string());
+        let mut result = MimeDetectionResult::from_content(mime_type, original_server_type);
+        result.confidence = MimeConfidence::Medium;
+        result
+    } else {
+        let mut result = MimeDetectionResult::fallback();
+        result.original_server_type = original_server_type;
+        return result;
+    };
+
+    if claimed.as_deref() == Some(result.mime_type.as_str()) {
+        result.confidence = MimeConfidence::VeryHigh;
+        result.detection_method = DetectionMethod::Hybrid;
+    } else if claimed.is_some() {
+        warn!("MIME type mismatch: claimed={:?}, content={} for file {}", claimed, result.mime_type, filename);
+    }
+    result
+}
+
+/// Whether a claimed type names the format of content detected as `detected` more
+/// precisely, e.g. an OpenDocument text detected as a ZIP archive
+fn refines(claimed: &str, detected: &str) -> bool {
+    match detected {
+        "application/zip" => ZIP_BASED_MIME_TYPES.contains(&claimed),
+        detected if OLE_BASED_MIME_TYPES.contains(&detected) => OLE_BASED_MIME_TYPES.contains(&claimed),
+        _ => false,
+    }
+}
+
+/// Whether content is text: UTF-16 with a byte order mark, or no NUL bytes and
+/// hardly any other control characters at its start
+fn looks_like_text(content: &[u8]) -> bool {
+    if content.starts_with(&[0xFF, 0xFE]) || content.starts_with(&[0xFE, 0xFF]) {
+        return true;
+    }
+    let sample = &content[..content.len().min(TEXT_SAMPLE_SIZE)];
+    if sample.contains(&0) {
+        return false;
+    }
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    control * 100 <= sample.len()
+}
+
+/// `text/plain; charset=utf-8` -> `text/plain`
+fn essence(mime_type: &str) -> String {
+    mime_type.split(';').next().unwrap_or("").trim().to_lowercase()
+}
+
+/// Whether files of this type may be ingested, per `ALLOWED_MIME_TYPES`
+pub fn is_allowed_mime_type(mime_type: &str) -> bool {
+    mime_type_in_allowlist(&ALLOWED_MIME_TYPES, mime_type)
+}
+
+/// Comma-separated types, `type/*` or `prefix*` patterns, or `*` for every type;
+/// the default list when unset or empty
+pub fn parse_allowlist(value: Option<&str>) -> Vec<String> {
+    let allowlist: Vec<String> = value
+        .unwrap_or("")
+        .split(',')
+        .map(|pattern| pattern.trim().to_lowercase())
+        .filter(|pattern| !pattern.is_empty())
+        .collect();
+    if allowlist.is_empty() {
+        DEFAULT_ALLOWED_MIME_TYPES.iter().map(|t| t.to_string()).collect()
+    } else {
+        allowlist
+    }
+}
+
+pub fn mime_type_in_allowlist(allowlist: &[String], mime_type: &str) -> bool {
+    let mime_type = essence(mime_type);
+    allowlist.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => mime_type.starts_with(prefix),
+        None => *pattern == mime_type,
+    })
+}
+
+/// The usual extension of a type, for rendering a file by what it is rather than
+/// what it is named
+pub fn extension_for_mime_type(mime_type: &str) -> Option<&'static str> {
+    let extension = match essence(mime_type).as_str() {
+        "application/pdf" => "pdf",
+        "image/jpeg" => "jpg",
+        "image/png" => "png",
+        "image/bmp" => "bmp",
+        "image/tiff" => "tiff",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "text/plain" => "txt",
+        "application/rtf" | "text/rtf" => "rtf",
+        "application/msword" => "doc",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "docx",
+        "application/vnd.oasis.opendocument.text" => "odt",
+        "application/vnd.ms-excel" => "xls",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "xlsx",
+        "application/vnd.oasis.opendocument.spreadsheet" => "ods",
+        "application/vnd.ms-powerpoint" => "ppt",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation" => "pptx",
+        "application/vnd.oasis.opendocument.presentation" => "odp",
+        _ => return None,
+    };
+    Some(extension)
+}
+
+/// The extension that decides how a document is rendered: the one its type implies,
+/// or its filename's for types that imply none
+pub fn rendering_extension(filename: &str, mime_type: &str) -> String {
+    match extension_for_mime_type(mime_type) {
+        Some(extension) => extension.to_string(),
+        None => Path::new(filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase(),
+    }
+}
+
+/// Ingestion was refused because the file's detected type is not in the allowlist
+#[derive(Debug)]
+pub struct UnsupportedFileType {
+    pub filename: String,
+    pub mime_type: String,
+}
+
+impl std::fmt::Display for UnsupportedFileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is a {} file, which is not an accepted type", self.filename, self.mime_type)
+    }
+}
+
+impl std::error::Error for UnsupportedFileType {}
+
 /// Detect MIME type from file extension using mime_guess library
 fn detect_from_extension(filename: &str, server_mime_type: Option<&str>) -> MimeDetectionResult {
     let path = Path::new(filename);
@@ -417,6 +646,70 @@ mod tests {
         assert_eq!(get_mime_type_from_extension("png"), "image/png");
     }
 
+    #[test]
+    fn test_upload_detection_trusts_content() {
+        // A PNG uploaded as a PDF is a PNG
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D, b'I', b'H', b'D', b'R'];
+        let result = detect_upload_mime(&png, "scan.pdf", Some("application/pdf"));
+        assert_eq!(result.mime_type, "image/png");
+        assert_eq!(result.original_server_type.as_deref(), Some("application/pdf"));
+
+        let result = detect_upload_mime(b"%PDF-1.7", "scan.pdf", Some("application/pdf; charset=binary"));
+        assert_eq!(result.mime_type, "application/pdf");
+        assert_eq!(result.detection_method, DetectionMethod::Hybrid);
+
+        // Binary content of no known format does not get the type it claims
+        let result = detect_upload_mime(&[0x00, 0x01, 0x02, 0x03, 0xFF], "scan.pdf", Some("application/pdf"));
+        assert_eq!(result.mime_type, "application/octet-stream");
+    }
+
+    #[test]
+    fn test_upload_detection_of_text() {
+        let result = detect_upload_mime(b"a,b\n1,2\n", "table.csv", Some("text/csv"));
+        assert_eq!(result.mime_type, "text/csv");
+
+        let result = detect_upload_mime(b"From: a@example.com\r\nSubject: Hi\r\n\r\nHello", "mail.eml", Some("message/rfc822"));
+        assert_eq!(result.mime_type, "message/rfc822");
+
+        // Text claiming an active or binary format is stored as plain text
+        let result = detect_upload_mime(b"<html><script>alert(1)</script></html>", "page.html", Some("text/html"));
+        assert_eq!(result.mime_type, "text/plain");
+        let result = detect_upload_mime(b"<?xml version=\"1.0\"?><a/>", "data.xml", Some("application/xml"));
+        assert_eq!(result.mime_type, "application/xml");
+        let result = detect_upload_mime(b"just some notes", "notes.pdf", Some("application/pdf"));
+        assert_eq!(result.mime_type, "text/plain");
+    }
+
+    #[test]
+    fn test_container_refinement() {
+        assert!(refines("application/vnd.oasis.opendocument.text", "application/zip"));
+        assert!(refines("application/vnd.ms-outlook", "application/msword"));
+        assert!(!refines("application/pdf", "application/zip"));
+        assert!(!refines("application/vnd.ms-outlook", "image/png"));
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let defaults = parse_allowlist(None);
+        assert!(mime_type_in_allowlist(&defaults, "application/pdf"));
+        assert!(mime_type_in_allowlist(&defaults, "image/heif"));
+        assert!(mime_type_in_allowlist(&defaults, "application/vnd.openxmlformats-officedocument.wordprocessingml.document"));
+        assert!(!mime_type_in_allowlist(&defaults, "application/octet-stream"));
+        assert!(!mime_type_in_allowlist(&defaults, "application/vnd.microsoft.portable-executable"));
+
+        let custom = parse_allowlist(Some(" application/pdf, IMAGE/* "));
+        assert!(mime_type_in_allowlist(&custom, "image/png"));
+        assert!(!mime_type_in_allowlist(&custom, "text/plain"));
+        assert!(mime_type_in_allowlist(&parse_allowlist(Some("*")), "application/zip"));
+    }
+
+    #[test]
+    fn test_rendering_extension() {
+        assert_eq!(rendering_extension("scan.pdf", "image/png"), "png");
+        assert_eq!(rendering_extension("letter", "application/vnd.oasis.opendocument.text"), "odt");
+        assert_eq!(rendering_extension("Archive.ZIP", "application/zip"), "zip");
+    }
+
     #[test]
     fn test_ocr_suitability() {
         let pdf_result = MimeDetectionResult::from_content("application/pdf".to_string(), None);
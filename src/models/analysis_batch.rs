@@ -0,0 +1,208 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::BulkOperationError;
+
+/// Most documents one analysis batch may cover
+pub const MAX_ANALYSIS_BATCH_DOCUMENTS: usize = 10_000;
+/// Most LLM requests one batch may have in flight
+pub const MAX_ANALYSIS_CONCURRENCY: i32 = 8;
+pub const DEFAULT_ANALYSIS_CONCURRENCY: i32 = 2;
+
+/// Characters counted as one token when estimating
+const CHARS_PER_TOKEN: i64 = 4;
+/// Tokens of the extraction instructions sent with every document
+const GRAPH_PROMPT_TOKENS: i64 = 100;
+/// Tokens a graph answer is expected to take
+const GRAPH_OUTPUT_TOKENS: i64 = 500;
+
+/// Which documents a batch analyzes. Documents without text are never included.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct AnalysisBatchFilter {
+    /// Only documents with this label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_id: Option<Uuid>,
+    /// Only documents imported from this source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<Uuid>,
+    /// Only documents that have no knowledge graph yet
+    #[serde(default)]
+    pub missing_graph_only: bool,
+}
+
+impl AnalysisBatchFilter {
+    /// Whether the filter would match every document
+    pub fn is_unrestricted(&self) -> bool {
+        self.label_id.is_none() && self.source_id.is_none() && !self.missing_graph_only
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AnalysisBatchRequest {
+    #[serde(flatten)]
+    pub filter: AnalysisBatchFilter,
+    /// Documents analyzed at the same time (default: 2)
+    pub concurrency: Option<i32>,
+}
+
+impl AnalysisBatchRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.filter.is_unrestricted() {
+            return Err("Give a label_id, a source_id or missing_graph_only".to_string());
+        }
+        match self.concurrency {
+            Some(concurrency) if !(1..=MAX_ANALYSIS_CONCURRENCY).contains(&concurrency) => {
+                Err(format!("concurrency must be between 1 and {}", MAX_ANALYSIS_CONCURRENCY))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn concurrency(&self) -> i32 {
+        self.concurrency.unwrap_or(DEFAULT_ANALYSIS_CONCURRENCY)
+    }
+}
+
+/// What analyzing the matching documents would cost, worked out before anything is sent
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AnalysisCostEstimate {
+    pub documents: i64,
+    /// More documents match than one batch may cover
+    pub exceeds_limit: bool,
+    pub model: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// In the currency of the configured token prices; None when no prices are set
+    pub estimated_cost: Option<f64>,
+}
+
+impl AnalysisCostEstimate {
+    /// Estimate from the number of documents and the characters of their text that are
+    /// sent, at the given prices per 1,000 input and output tokens
+    pub fn new(documents: i64, sent_chars: i64, model: &str, prices: Option<(f64, f64)>) -> Self {
+        let input_tokens = (sent_chars + CHARS_PER_TOKEN - 1) / CHARS_PER_TOKEN + documents * GRAPH_PROMPT_TOKENS;
+        let output_tokens = documents * GRAPH_OUTPUT_TOKENS;
+        Self {
+            documents,
+            exceeds_limit: documents > MAX_ANALYSIS_BATCH_DOCUMENTS as i64,
+            model: model.to_string(),
+            input_tokens,
+            output_tokens,
+            estimated_cost: prices.map(|(input, output)| {
+                (input_tokens as f64 * input + output_tokens as f64 * output) / 1000.0
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisBatchStatus {
+    Queued,
+    /// In progress, or interrupted and resumed at the next start
+    Running,
+    /// Every document was processed; some may have failed
+    Completed,
+    /// Stopped by an error that affects every document
+    Failed,
+    /// Stopped by the user; documents already analyzed keep their graphs
+    Cancelled,
+}
+
+impl AnalysisBatchStatus {
+    pub fn is_finished(self) -> bool {
+        !matches!(self, AnalysisBatchStatus::Queued | AnalysisBatchStatus::Running)
+    }
+}
+
+impl std::fmt::Display for AnalysisBatchStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisBatchStatus::Queued => write!(f, "queued"),
+            AnalysisBatchStatus::Running => write!(f, "running"),
+            AnalysisBatchStatus::Completed => write!(f, "completed"),
+            AnalysisBatchStatus::Failed => write!(f, "failed"),
+            AnalysisBatchStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl TryFrom<String> for AnalysisBatchStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "queued" => Ok(AnalysisBatchStatus::Queued),
+            "running" => Ok(AnalysisBatchStatus::Running),
+            "completed" => Ok(AnalysisBatchStatus::Completed),
+            "failed" => Ok(AnalysisBatchStatus::Failed),
+            "cancelled" => Ok(AnalysisBatchStatus::Cancelled),
+            _ => Err(format!("Unknown analysis batch status: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AnalysisBatch {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    #[schema(value_type = AnalysisBatchFilter)]
+    pub filter: sqlx::types::Json<AnalysisBatchFilter>,
+    pub concurrency: i32,
+    pub model: String,
+    #[sqlx(try_from = "String")]
+    pub status: AnalysisBatchStatus,
+    pub total_documents: i32,
+    pub processed_documents: i32,
+    pub succeeded_documents: i32,
+    pub failed_documents: i32,
+    /// The first failures; `failed_documents` counts them all
+    #[schema(value_type = Vec<BulkOperationError>)]
+    pub errors: sqlx::types::Json<Vec<BulkOperationError>>,
+    pub estimated_input_tokens: i64,
+    pub estimated_output_tokens: i64,
+    pub estimated_cost: Option<f64>,
+    /// Why the batch failed as a whole
+    pub error: Option<String>,
+    pub cancelled_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let request: AnalysisBatchRequest = serde_json::from_value(serde_json::json!({
+            "label_id": Uuid::nil(),
+            "missing_graph_only": true,
+        }))
+        .unwrap();
+        assert!(request.validate().is_ok());
+        assert_eq!(request.concurrency(), DEFAULT_ANALYSIS_CONCURRENCY);
+
+        let everything = AnalysisBatchRequest { filter: AnalysisBatchFilter::default(), concurrency: None };
+        assert!(everything.validate().is_err());
+        assert!(AnalysisBatchRequest { concurrency: Some(0), ..request.clone() }.validate().is_err());
+        assert!(AnalysisBatchRequest { concurrency: Some(MAX_ANALYSIS_CONCURRENCY + 1), ..request }
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_cost_estimate() {
+        let estimate = AnalysisCostEstimate::new(10, 40_000, "gpt-4o-mini", Some((0.5, 1.5)));
+        assert_eq!(estimate.input_tokens, 11_000);
+        assert_eq!(estimate.output_tokens, 5_000);
+        assert_eq!(estimate.estimated_cost, Some(13.0));
+        assert!(!estimate.exceeds_limit);
+
+        assert_eq!(AnalysisCostEstimate::new(1, 10, "gpt-4o-mini", None).estimated_cost, None);
+    }
+}
@@ -0,0 +1,213 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Longest annotation note or comment accepted
+pub const MAX_ANNOTATION_TEXT_LENGTH: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "annotation_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationKind {
+    /// Marks a passage of text
+    Highlight,
+    /// Frames an area of the page
+    Rectangle,
+    /// A pin on a point of the page that starts a discussion
+    Comment,
+}
+
+/// A highlight, rectangle or comment anchored to a spot on a document page. Positions
+/// and sizes are fractions of the page's width and height, from its top left corner.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentAnnotation {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    /// None once the author's account is deleted
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub kind: AnnotationKind,
+    pub page: i32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub color: Option<String>,
+    /// The highlighted text or the note of a rectangle or comment
+    pub text: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A comment in an annotation's thread
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AnnotationComment {
+    pub id: Uuid,
+    pub annotation_id: Uuid,
+    /// The comment this one replies to
+    pub parent_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An annotation with every comment of its thread, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AnnotationResponse {
+    #[serde(flatten)]
+    pub annotation: DocumentAnnotation,
+    pub comments: Vec<AnnotationComment>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct AnnotationListQuery {
+    /// Only the annotations of this page
+    pub page: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateAnnotationRequest {
+    pub kind: AnnotationKind,
+    pub page: i32,
+    pub x: f64,
+    pub y: f64,
+    #[serde(default)]
+    pub width: f64,
+    #[serde(default)]
+    pub height: f64,
+    pub color: Option<String>,
+    pub text: Option<String>,
+}
+
+impl CreateAnnotationRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.page < 1 {
+            return Err("page must be 1 or more".to_string());
+        }
+        validate_area(self.x, self.y, self.width, self.height)?;
+        if self.kind != AnnotationKind::Comment && (self.width == 0.0 || self.height == 0.0) {
+            return Err("highlights and rectangles need a width and height".to_string());
+        }
+        validate_color(self.color.as_deref())?;
+        validate_text(self.text.as_deref())
+    }
+}
+
+/// Move or resize an annotation or change its color or note; omitted fields are kept
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateAnnotationRequest {
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub color: Option<String>,
+    pub text: Option<String>,
+}
+
+impl UpdateAnnotationRequest {
+    /// Validates the annotation as it would be after the update
+    pub fn validate(&self, current: &DocumentAnnotation) -> Result<(), String> {
+        validate_area(
+            self.x.unwrap_or(current.x),
+            self.y.unwrap_or(current.y),
+            self.width.unwrap_or(current.width),
+            self.height.unwrap_or(current.height),
+        )?;
+        validate_color(self.color.as_deref())?;
+        validate_text(self.text.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateAnnotationCommentRequest {
+    pub body: String,
+    /// Reply to this comment of the same annotation
+    pub parent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateAnnotationCommentRequest {
+    pub body: String,
+}
+
+pub fn validate_comment_body(body: &str) -> Result<(), String> {
+    if body.trim().is_empty() {
+        return Err("body is empty".to_string());
+    }
+    validate_text(Some(body))
+}
+
+fn validate_area(x: f64, y: f64, width: f64, height: f64) -> Result<(), String> {
+    let fraction = |v: f64| (0.0..=1.0).contains(&v);
+    if !fraction(x) || !fraction(y) || !fraction(width) || !fraction(height) {
+        return Err("x, y, width and height must be fractions of the page between 0 and 1".to_string());
+    }
+    if x + width > 1.0 || y + height > 1.0 {
+        return Err("the annotation must stay within the page".to_string());
+    }
+    Ok(())
+}
+
+fn validate_color(color: Option<&str>) -> Result<(), String> {
+    let is_hex_color = |c: &str| {
+        c.len() == 7 && c.starts_with('#') && c[1..].chars().all(|ch| ch.is_ascii_hexdigit())
+    };
+    match color {
+        Some(color) if !is_hex_color(color) => Err("color must look like #ffcc00".to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn validate_text(text: Option<&str>) -> Result<(), String> {
+    if text.is_some_and(|text| text.chars().count() > MAX_ANNOTATION_TEXT_LENGTH) {
+        return Err(format!("text is longer than {} characters", MAX_ANNOTATION_TEXT_LENGTH));
+    }
+    Ok(())
+}
+
+/// A change to a document's annotations, streamed to everyone viewing it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum AnnotationChange {
+    AnnotationCreated { annotation: DocumentAnnotation },
+    AnnotationUpdated { annotation: DocumentAnnotation },
+    AnnotationDeleted { annotation_id: Uuid },
+    CommentCreated { comment: AnnotationComment },
+    CommentUpdated { comment: AnnotationComment },
+    CommentDeleted { annotation_id: Uuid, comment_id: Uuid },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(kind: AnnotationKind, x: f64, width: f64) -> CreateAnnotationRequest {
+        CreateAnnotationRequest {
+            kind,
+            page: 1,
+            x,
+            y: 0.1,
+            width,
+            height: if width > 0.0 { 0.05 } else { 0.0 },
+            color: Some("#ffcc00".to_string()),
+            text: None,
+        }
+    }
+
+    #[test]
+    fn test_create_annotation_validation() {
+        assert!(request(AnnotationKind::Highlight, 0.2, 0.5).validate().is_ok());
+        assert!(request(AnnotationKind::Comment, 0.9, 0.0).validate().is_ok());
+        // Highlights need an area, and nothing may reach past the page
+        assert!(request(AnnotationKind::Highlight, 0.2, 0.0).validate().is_err());
+        assert!(request(AnnotationKind::Rectangle, 0.6, 0.5).validate().is_err());
+
+        let mut bad_color = request(AnnotationKind::Comment, 0.5, 0.0);
+        bad_color.color = Some("yellow".to_string());
+        assert!(bad_color.validate().is_err());
+    }
+}
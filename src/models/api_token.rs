@@ -0,0 +1,120 @@
+use axum::http::Method;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// What requests an API token may make on behalf of its user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "api_token_scope", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ApiTokenScope {
+    /// GET, HEAD and OPTIONS requests, GraphQL queries and browsing the WebDAV library
+    ReadOnly,
+    /// Uploading documents, and `GET /api/auth/me` to check the token works
+    UploadOnly,
+    /// Everything the user can do, except managing API tokens
+    Full,
+}
+
+impl ApiTokenScope {
+    /// Whether a request to `path`, the full path including `/api`, is within the scope.
+    /// Tokens, passkeys and calendar feed tokens are managed only from a logged in
+    /// session, so no token can mint another credential.
+    pub fn allows(self, method: &Method, path: &str) -> bool {
+        let path = path.trim_end_matches('/');
+        if path.starts_with("/api/users/me/tokens")
+            || path.starts_with("/api/users/me/passkeys")
+            || path.starts_with("/api/users/me/calendar-feed")
+        {
+            return false;
+        }
+
+        match self {
+            ApiTokenScope::Full => true,
+            // The GraphQL schema has no mutations, so POSTing a query only reads, and
+            // PROPFIND lists the read-only WebDAV library
+            ApiTokenScope::ReadOnly => {
+                method == Method::GET
+                    || method == Method::HEAD
+                    || method == Method::OPTIONS
+                    || (method == Method::POST && path == "/api/graphql")
+                    || (method.as_str() == "PROPFIND" && (path == "/dav" || path.starts_with("/dav/")))
+            }
+            ApiTokenScope::UploadOnly => {
+                (method == Method::POST && path == "/api/documents") || (method == Method::GET && path == "/api/auth/me")
+            }
+        }
+    }
+}
+
+/// A personal access token. The secret is only returned when the token is created.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// Start of the token, to recognise it in lists
+    pub token_prefix: String,
+    pub scope: ApiTokenScope,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiToken {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateApiTokenRequest {
+    /// What the token is for, e.g. "Office scanner"
+    pub name: String,
+    pub scope: ApiTokenScope,
+    /// The token stops working after this many days; never when omitted
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CreateApiTokenResponse {
+    pub token: ApiToken,
+    /// Send as `Authorization: Bearer <secret>`; store it now, it cannot be retrieved again
+    pub secret: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_token_scopes() {
+        assert!(ApiTokenScope::ReadOnly.allows(&Method::GET, "/api/documents/123/download"));
+        assert!(!ApiTokenScope::ReadOnly.allows(&Method::POST, "/api/documents"));
+        assert!(!ApiTokenScope::ReadOnly.allows(&Method::DELETE, "/api/documents/123"));
+        assert!(ApiTokenScope::ReadOnly.allows(&Method::POST, "/api/graphql"));
+        let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+        assert!(ApiTokenScope::ReadOnly.allows(&propfind, "/dav/Labels/"));
+        assert!(!ApiTokenScope::ReadOnly.allows(&propfind, "/api/documents"));
+
+        assert!(ApiTokenScope::UploadOnly.allows(&Method::POST, "/api/documents"));
+        assert!(ApiTokenScope::UploadOnly.allows(&Method::POST, "/api/documents/"));
+        assert!(ApiTokenScope::UploadOnly.allows(&Method::GET, "/api/auth/me"));
+        assert!(!ApiTokenScope::UploadOnly.allows(&Method::GET, "/api/documents"));
+        assert!(!ApiTokenScope::UploadOnly.allows(&Method::POST, "/api/documents/bulk/delete"));
+
+        assert!(ApiTokenScope::Full.allows(&Method::DELETE, "/api/documents/123"));
+    }
+
+    #[test]
+    fn test_api_tokens_cannot_manage_tokens() {
+        for scope in [ApiTokenScope::ReadOnly, ApiTokenScope::UploadOnly, ApiTokenScope::Full] {
+            assert!(!scope.allows(&Method::GET, "/api/users/me/tokens"));
+            assert!(!scope.allows(&Method::POST, "/api/users/me/tokens"));
+            assert!(!scope.allows(&Method::POST, "/api/users/me/passkeys/register/start"));
+            assert!(!scope.allows(&Method::POST, "/api/users/me/calendar-feed"));
+        }
+    }
+}
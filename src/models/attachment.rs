@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Link between an ingested email and a document created from one of its attachments
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct DocumentAttachment {
+    pub id: Uuid,
+    /// The email document
+    pub parent_document_id: Uuid,
+    /// The document created from the attachment
+    pub child_document_id: Uuid,
+    /// Attachment filename as given in the email
+    pub filename: String,
+    /// 1-based position of the attachment within the email
+    pub position: i32,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Longest voice name accepted
+const MAX_VOICE_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioStatus {
+    Queued,
+    /// Being spoken, or interrupted and started over at the next start
+    Running,
+    /// The narration can be played
+    Completed,
+    Failed,
+}
+
+impl std::fmt::Display for AudioStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioStatus::Queued => write!(f, "queued"),
+            AudioStatus::Running => write!(f, "running"),
+            AudioStatus::Completed => write!(f, "completed"),
+            AudioStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl TryFrom<String> for AudioStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "queued" => Ok(AudioStatus::Queued),
+            "running" => Ok(AudioStatus::Running),
+            "completed" => Ok(AudioStatus::Completed),
+            "failed" => Ok(AudioStatus::Failed),
+            _ => Err(format!("Unknown audio status: {}", value)),
+        }
+    }
+}
+
+/// The spoken narration of a document's text
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentAudio {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    #[sqlx(try_from = "String")]
+    pub status: AudioStatus,
+    pub voice: String,
+    pub model: String,
+    /// Characters of the document's text that are narrated
+    pub source_chars: i32,
+    /// Where the audio is stored; kept from the last narration while a new one is made
+    #[serde(skip_serializing)]
+    pub file_path: Option<String>,
+    /// Size of the audio in bytes, once there is one
+    pub file_size: Option<i64>,
+    pub error: Option<String>,
+    pub requested_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl DocumentAudio {
+    /// Whether the narration is still being made
+    pub fn is_pending(&self) -> bool {
+        matches!(self.status, AudioStatus::Queued | AudioStatus::Running)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct NarrateQuery {
+    /// Voice of the text-to-speech API to speak with instead of `TTS_VOICE`
+    pub voice: Option<String>,
+}
+
+impl NarrateQuery {
+    /// The voice asked for, trimmed; None when left empty
+    pub fn voice(&self) -> Result<Option<&str>, String> {
+        let Some(voice) = self.voice.as_deref().map(str::trim).filter(|voice| !voice.is_empty()) else {
+            return Ok(None);
+        };
+        if voice.len() > MAX_VOICE_LENGTH {
+            return Err(format!("Voice must be at most {} characters", MAX_VOICE_LENGTH));
+        }
+        if !voice.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+            return Err("Voice may only contain letters, digits, '-', '_' and '.'".to_string());
+        }
+        Ok(Some(voice))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(voice: Option<&str>) -> NarrateQuery {
+        NarrateQuery { voice: voice.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_narrate_query_voice() {
+        assert_eq!(query(None).voice(), Ok(None));
+        assert_eq!(query(Some("  ")).voice(), Ok(None));
+        assert_eq!(query(Some(" nova ")).voice(), Ok(Some("nova")));
+        assert_eq!(query(Some("en_US-lessac-medium")).voice(), Ok(Some("en_US-lessac-medium")));
+        assert!(query(Some("a voice")).voice().is_err());
+        assert!(query(Some(&"a".repeat(65))).voice().is_err());
+    }
+
+    #[test]
+    fn test_audio_status_round_trip() {
+        for status in [AudioStatus::Queued, AudioStatus::Running, AudioStatus::Completed, AudioStatus::Failed] {
+            assert_eq!(AudioStatus::try_from(status.to_string()), Ok(status));
+        }
+        assert!(AudioStatus::try_from("done".to_string()).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// A recorded action, with who took it, from where, and what changed
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    /// None for actions taken by the system and for failed logins
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    /// e.g. `document.download` or `settings.update`
+    pub action: String,
+    /// e.g. `document`, `label`, `settings` or `user`
+    pub resource_type: String,
+    pub resource_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// The admin who took the action while impersonating the user
+    pub impersonator_id: Option<Uuid>,
+    pub impersonator_username: Option<String>,
+    /// Changed values before the action
+    pub before: Option<serde_json::Value>,
+    /// Changed values after the action
+    pub after: Option<serde_json::Value>,
+    pub details: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct AuditLogQuery {
+    /// Only actions taken by this user
+    pub user_id: Option<Uuid>,
+    /// Only this action, e.g. `document.delete`, or every action starting with a prefix
+    /// ending in `.`, e.g. `document.`
+    pub action: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    /// Only actions taken while impersonating a user (true) or only the others (false)
+    pub impersonated: Option<bool>,
+    /// Only actions taken by this admin while impersonating a user
+    pub impersonator_id: Option<Uuid>,
+    /// Only actions at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only actions before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Default 50, at most 500
+    pub limit: Option<i64>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Deprecated: use `cursor`. Entries to skip; cannot be combined with `cursor`.
+    pub offset: Option<i64>,
+    /// Count the matching entries, default true; skip it for faster pages of a large log
+    pub include_total: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditLogResponse {
+    /// Newest first
+    pub entries: Vec<AuditLogEntry>,
+    /// Entries matching the filters; left out with `include_total=false`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    /// Cursor of the next page, None on the last one
+    pub next_cursor: Option<String>,
+}
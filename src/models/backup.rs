@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStatus {
+    /// Being exported or uploaded
+    Running,
+    /// Uploaded to the destination
+    Completed,
+    Failed,
+    /// Removed from the destination to keep the configured number of backups
+    Pruned,
+}
+
+impl std::fmt::Display for BackupStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackupStatus::Running => write!(f, "running"),
+            BackupStatus::Completed => write!(f, "completed"),
+            BackupStatus::Failed => write!(f, "failed"),
+            BackupStatus::Pruned => write!(f, "pruned"),
+        }
+    }
+}
+
+impl TryFrom<String> for BackupStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "running" => Ok(BackupStatus::Running),
+            "completed" => Ok(BackupStatus::Completed),
+            "failed" => Ok(BackupStatus::Failed),
+            "pruned" => Ok(BackupStatus::Pruned),
+            _ => Err(format!("Unknown backup status: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    /// Every document in the library
+    Full,
+    /// Documents created or changed since the backup before it
+    Incremental,
+}
+
+impl std::fmt::Display for BackupKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackupKind::Full => write!(f, "full"),
+            BackupKind::Incremental => write!(f, "incremental"),
+        }
+    }
+}
+
+impl TryFrom<String> for BackupKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "full" => Ok(BackupKind::Full),
+            "incremental" => Ok(BackupKind::Incremental),
+            _ => Err(format!("Unknown backup kind: {}", value)),
+        }
+    }
+}
+
+/// A library export uploaded to the backup destination
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Backup {
+    pub id: Uuid,
+    /// The export that wrote the archive, until it is uploaded
+    pub export_id: Option<Uuid>,
+    /// `s3` or `webdav`
+    pub destination: String,
+    #[sqlx(try_from = "String")]
+    pub kind: BackupKind,
+    /// Start of the backup an incremental backup continues from
+    pub changed_since: Option<DateTime<Utc>>,
+    #[sqlx(try_from = "String")]
+    pub status: BackupStatus,
+    /// The admin who started it; None when it was scheduled
+    pub triggered_by: Option<Uuid>,
+    /// S3 key or WebDAV URL of the archive
+    pub remote_path: Option<String>,
+    pub archive_size: Option<i64>,
+    pub total_documents: i32,
+    /// Documents backed up without their file
+    pub failed_documents: i32,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub pruned_at: Option<DateTime<Utc>>,
+}
+
+/// The backup configuration and how recent backups went
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BackupOverview {
+    /// Whether `BACKUP_DESTINATION` is set and valid
+    pub enabled: bool,
+    /// Why the configuration could not be used
+    pub configuration_error: Option<String>,
+    /// `s3` or `webdav`
+    pub destination: Option<String>,
+    /// Bucket and prefix, or collection URL, that backups are uploaded to
+    pub target: Option<String>,
+    /// `full` or `incremental`
+    pub mode: Option<String>,
+    pub interval_hours: Option<u64>,
+    /// Full backups kept at the destination, with the incremental backups after them
+    pub retention_count: Option<usize>,
+    pub last_backup: Option<Backup>,
+    pub last_successful_at: Option<DateTime<Utc>>,
+    pub next_backup_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_and_kind_round_trip() {
+        for status in [BackupStatus::Running, BackupStatus::Completed, BackupStatus::Failed, BackupStatus::Pruned] {
+            assert_eq!(BackupStatus::try_from(status.to_string()), Ok(status));
+        }
+        for kind in [BackupKind::Full, BackupKind::Incremental] {
+            assert_eq!(BackupKind::try_from(kind.to_string()), Ok(kind));
+        }
+        assert!(BackupKind::try_from("differential".to_string()).is_err());
+    }
+}
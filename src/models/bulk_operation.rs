@@ -0,0 +1,260 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::SavedSearchQuery;
+
+/// Most documents one bulk operation may cover
+pub const MAX_BULK_DOCUMENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkOperationKind {
+    AddLabels,
+    RemoveLabels,
+    Delete,
+    /// Reset the OCR results and queue the documents again
+    ReprocessOcr,
+    /// Give the documents to another user; admins only
+    ChangeOwner,
+    /// Combine the PDF documents into one, in the order given
+    Merge,
+    /// Rename the documents to titles generated from their contents
+    Retitle,
+}
+
+impl std::fmt::Display for BulkOperationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BulkOperationKind::AddLabels => write!(f, "add_labels"),
+            BulkOperationKind::RemoveLabels => write!(f, "remove_labels"),
+            BulkOperationKind::Delete => write!(f, "delete"),
+            BulkOperationKind::ReprocessOcr => write!(f, "reprocess_ocr"),
+            BulkOperationKind::ChangeOwner => write!(f, "change_owner"),
+            BulkOperationKind::Merge => write!(f, "merge"),
+            BulkOperationKind::Retitle => write!(f, "retitle"),
+        }
+    }
+}
+
+impl TryFrom<String> for BulkOperationKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "add_labels" => Ok(BulkOperationKind::AddLabels),
+            "remove_labels" => Ok(BulkOperationKind::RemoveLabels),
+            "delete" => Ok(BulkOperationKind::Delete),
+            "reprocess_ocr" => Ok(BulkOperationKind::ReprocessOcr),
+            "change_owner" => Ok(BulkOperationKind::ChangeOwner),
+            "merge" => Ok(BulkOperationKind::Merge),
+            "retitle" => Ok(BulkOperationKind::Retitle),
+            _ => Err(format!("Unknown bulk operation: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkOperationStatus {
+    Queued,
+    /// In progress, or interrupted and resumed at the next start
+    Running,
+    /// Every document was processed; some may have failed
+    Completed,
+    /// Stopped by an error that affects every document
+    Failed,
+}
+
+impl std::fmt::Display for BulkOperationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BulkOperationStatus::Queued => write!(f, "queued"),
+            BulkOperationStatus::Running => write!(f, "running"),
+            BulkOperationStatus::Completed => write!(f, "completed"),
+            BulkOperationStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl TryFrom<String> for BulkOperationStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "queued" => Ok(BulkOperationStatus::Queued),
+            "running" => Ok(BulkOperationStatus::Running),
+            "completed" => Ok(BulkOperationStatus::Completed),
+            "failed" => Ok(BulkOperationStatus::Failed),
+            _ => Err(format!("Unknown bulk operation status: {}", value)),
+        }
+    }
+}
+
+/// What an operation needs besides the documents
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct BulkOperationParameters {
+    /// Labels to add or remove
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub label_ids: Vec<Uuid>,
+    /// New owner of the documents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_id: Option<Uuid>,
+    /// Name of the merged document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    /// Delete the documents once they are merged
+    #[serde(default)]
+    pub delete_originals: bool,
+    /// How titles are generated: `auto` (default), `llm` or `heuristic`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_mode: Option<String>,
+    /// Retitle documents whose names do not look like scanner output as well
+    #[serde(default)]
+    pub include_named: bool,
+}
+
+/// An operation on the listed documents, or on those matching a search
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct BulkOperationRequest {
+    pub operation: BulkOperationKind,
+    pub document_ids: Option<Vec<Uuid>>,
+    /// Search whose matches, oldest first, are the documents; needs a query or filters
+    pub filter: Option<SavedSearchQuery>,
+    #[serde(flatten)]
+    pub parameters: BulkOperationParameters,
+}
+
+impl BulkOperationRequest {
+    /// What is wrong with the request, before its documents are resolved
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.document_ids, &self.filter) {
+            (Some(_), Some(_)) => return Err("Give either document_ids or a filter, not both".to_string()),
+            (None, None) => return Err("Give document_ids or a filter".to_string()),
+            (Some(ids), None) if ids.is_empty() => return Err("document_ids is empty".to_string()),
+            (Some(ids), None) if ids.len() > MAX_BULK_DOCUMENTS => {
+                return Err(format!("At most {} documents are allowed", MAX_BULK_DOCUMENTS));
+            }
+            (None, Some(filter)) if filter.is_unrestricted() => {
+                return Err("A filter needs a query, tags or MIME types".to_string());
+            }
+            _ => {}
+        }
+
+        match self.operation {
+            BulkOperationKind::AddLabels | BulkOperationKind::RemoveLabels if self.parameters.label_ids.is_empty() => {
+                Err("label_ids is required".to_string())
+            }
+            BulkOperationKind::ChangeOwner if self.parameters.owner_id.is_none() => {
+                Err("owner_id is required".to_string())
+            }
+            BulkOperationKind::Merge if self.document_ids.as_ref().is_some_and(|ids| ids.len() < 2) => {
+                Err("At least two documents are required to merge".to_string())
+            }
+            BulkOperationKind::Retitle
+                if self.parameters.title_mode.as_deref().is_some_and(|mode| !super::TITLE_MODES.contains(&mode)) =>
+            {
+                Err("title_mode must be auto, llm or heuristic".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkOperationError {
+    pub document_id: Uuid,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BulkOperation {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    #[sqlx(try_from = "String")]
+    pub operation: BulkOperationKind,
+    #[schema(value_type = BulkOperationParameters)]
+    pub parameters: sqlx::types::Json<BulkOperationParameters>,
+    #[sqlx(try_from = "String")]
+    pub status: BulkOperationStatus,
+    pub total_documents: i32,
+    pub processed_documents: i32,
+    pub succeeded_documents: i32,
+    pub failed_documents: i32,
+    /// The first failures; `failed_documents` counts them all
+    #[schema(value_type = Vec<BulkOperationError>)]
+    pub errors: sqlx::types::Json<Vec<BulkOperationError>>,
+    /// The document a merge created
+    pub merged_document_id: Option<Uuid>,
+    /// Why the operation failed as a whole
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(operation: BulkOperationKind) -> BulkOperationRequest {
+        BulkOperationRequest {
+            operation,
+            document_ids: Some(vec![Uuid::new_v4(), Uuid::new_v4()]),
+            filter: None,
+            parameters: BulkOperationParameters::default(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(request(BulkOperationKind::Delete).validate().is_ok());
+        assert!(request(BulkOperationKind::AddLabels).validate().is_err());
+        assert!(request(BulkOperationKind::ChangeOwner).validate().is_err());
+
+        let labels = BulkOperationRequest {
+            parameters: BulkOperationParameters { label_ids: vec![Uuid::new_v4()], ..Default::default() },
+            ..request(BulkOperationKind::RemoveLabels)
+        };
+        assert!(labels.validate().is_ok());
+
+        let single_merge = BulkOperationRequest { document_ids: Some(vec![Uuid::new_v4()]), ..request(BulkOperationKind::Merge) };
+        assert!(single_merge.validate().is_err());
+
+        assert!(request(BulkOperationKind::Retitle).validate().is_ok());
+        let unknown_mode = BulkOperationRequest {
+            parameters: BulkOperationParameters { title_mode: Some("guess".to_string()), ..Default::default() },
+            ..request(BulkOperationKind::Retitle)
+        };
+        assert!(unknown_mode.validate().is_err());
+
+        let everything = BulkOperationRequest {
+            document_ids: None,
+            filter: Some(SavedSearchQuery::default()),
+            ..request(BulkOperationKind::Delete)
+        };
+        assert!(everything.validate().is_err());
+
+        let invoices = BulkOperationRequest {
+            document_ids: None,
+            filter: Some(SavedSearchQuery { query: "label:Invoices".to_string(), ..Default::default() }),
+            ..request(BulkOperationKind::ReprocessOcr)
+        };
+        assert!(invoices.validate().is_ok());
+    }
+
+    #[test]
+    fn test_request_parameters_are_flattened() {
+        let request: BulkOperationRequest = serde_json::from_value(serde_json::json!({
+            "operation": "change_owner",
+            "document_ids": [Uuid::nil()],
+            "owner_id": Uuid::nil(),
+        }))
+        .unwrap();
+        assert_eq!(request.operation, BulkOperationKind::ChangeOwner);
+        assert_eq!(request.parameters.owner_id, Some(Uuid::nil()));
+        assert!(request.validate().is_ok());
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::is_valid_field_name;
+
+/// Reminders one feed may ask calendars for per event
+pub const MAX_CALENDAR_LEAD_TIMES: usize = 5;
+/// Furthest ahead a reminder may be, in days
+pub const MAX_CALENDAR_LEAD_DAYS: i32 = 365;
+const MAX_CALENDAR_DATE_FIELDS: usize = 20;
+
+/// A user's iCalendar feed of document dates. The token is not part of it; it is only
+/// returned when the feed is created or its token replaced.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CalendarFeed {
+    pub user_id: Uuid,
+    /// Days before each date that calendars remind of it, e.g. `[7, 1]`
+    pub lead_days: Vec<i32>,
+    /// Date custom fields that become events, e.g. `due_date`
+    pub date_fields: Vec<String>,
+    /// When a calendar last fetched the feed
+    pub last_accessed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CreateCalendarFeedResponse {
+    pub feed: CalendarFeed,
+    /// Secret part of the feed URL; store it now, it cannot be retrieved again
+    pub token: String,
+    /// Path of the feed to subscribe to, relative to the server
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateCalendarFeedRequest {
+    pub lead_days: Option<Vec<i32>>,
+    pub date_fields: Option<Vec<String>>,
+}
+
+impl UpdateCalendarFeedRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(lead_days) = &self.lead_days {
+            if lead_days.len() > MAX_CALENDAR_LEAD_TIMES {
+                return Err(format!("At most {} reminders per date", MAX_CALENDAR_LEAD_TIMES));
+            }
+            if lead_days.iter().any(|days| !(0..=MAX_CALENDAR_LEAD_DAYS).contains(days)) {
+                return Err(format!("Reminders must be 0 to {} days ahead", MAX_CALENDAR_LEAD_DAYS));
+            }
+        }
+        if let Some(date_fields) = &self.date_fields {
+            if date_fields.is_empty() || date_fields.len() > MAX_CALENDAR_DATE_FIELDS {
+                return Err(format!("Give 1 to {} date fields", MAX_CALENDAR_DATE_FIELDS));
+            }
+            if let Some(name) = date_fields.iter().find(|name| !is_valid_field_name(name)) {
+                return Err(format!("'{}' is not a custom field name", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A date of a document that appears in the feed
+#[derive(Debug, Clone, FromRow)]
+pub struct CalendarEntry {
+    pub document_id: Uuid,
+    pub filename: String,
+    pub document_type: String,
+    /// Name of the custom field the date is from
+    pub field: String,
+    pub date: NaiveDate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_calendar_feed_validation() {
+        let request = UpdateCalendarFeedRequest {
+            lead_days: Some(vec![14, 1, 0]),
+            date_fields: Some(vec!["due_date".to_string(), "renewal_date".to_string()]),
+        };
+        assert!(request.validate().is_ok());
+        assert!(UpdateCalendarFeedRequest::default().validate().is_ok());
+
+        let too_early = UpdateCalendarFeedRequest { lead_days: Some(vec![400]), ..Default::default() };
+        assert!(too_early.validate().is_err());
+        let no_fields = UpdateCalendarFeedRequest { date_fields: Some(vec![]), ..Default::default() };
+        assert!(no_fields.validate().is_err());
+        let bad_name = UpdateCalendarFeedRequest { date_fields: Some(vec!["Due Date".to_string()]), ..Default::default() };
+        assert!(bad_name.validate().is_err());
+    }
+}
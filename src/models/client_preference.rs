@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+const MAX_NAMESPACE_LENGTH: usize = 64;
+/// Serialized size of one namespace's value
+pub const MAX_CLIENT_PREFERENCE_BYTES: usize = 64 * 1024;
+/// Namespaces a user may keep
+pub const MAX_CLIENT_PREFERENCE_NAMESPACES: i64 = 100;
+
+/// Preferences a client keeps on the server under its own namespace, such as the columns
+/// and sort order of the web interface's document list
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ClientPreference {
+    /// e.g. `web.document-list`
+    pub namespace: String,
+    /// Version of the value's layout, chosen by the client
+    pub schema_version: i32,
+    #[schema(value_type = Object)]
+    pub value: serde_json::Value,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateClientPreferenceRequest {
+    /// Default 1. Lower than the stored version answers 409, so an outdated client
+    /// cannot overwrite what a newer one saved.
+    pub schema_version: Option<i32>,
+    #[schema(value_type = Object)]
+    pub value: serde_json::Value,
+}
+
+impl UpdateClientPreferenceRequest {
+    pub fn schema_version(&self) -> i32 {
+        self.schema_version.unwrap_or(1)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.schema_version() < 1 {
+            return Err("Schema version must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether the value fits within `MAX_CLIENT_PREFERENCE_BYTES`
+    pub fn fits(&self) -> bool {
+        serde_json::to_vec(&self.value).map_or(false, |bytes| bytes.len() <= MAX_CLIENT_PREFERENCE_BYTES)
+    }
+}
+
+/// Lowercase letters, digits, dots, dashes and underscores, starting with a letter or
+/// digit, so clients can prefix their own name: `web.document-list`, `ios.viewer`
+pub fn validate_preference_namespace(namespace: &str) -> Result<(), String> {
+    let valid_chars = namespace
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '-' | '_'));
+    let starts_well = namespace.chars().next().is_some_and(|c| c.is_ascii_alphanumeric());
+    if namespace.len() > MAX_NAMESPACE_LENGTH || !valid_chars || !starts_well {
+        return Err(format!(
+            "The namespace must be 1 to {} lowercase letters, digits, dots, dashes and underscores",
+            MAX_NAMESPACE_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_namespace_validation() {
+        assert!(validate_preference_namespace("web.document-list").is_ok());
+        assert!(validate_preference_namespace("ios_viewer2").is_ok());
+        assert!(validate_preference_namespace("").is_err());
+        assert!(validate_preference_namespace(".hidden").is_err());
+        assert!(validate_preference_namespace("Web.List").is_err());
+        assert!(validate_preference_namespace("web/list").is_err());
+        assert!(validate_preference_namespace(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_value_size_limit() {
+        let small = UpdateClientPreferenceRequest { schema_version: None, value: json!({ "columns": ["title", "date"] }) };
+        assert!(small.fits());
+        assert_eq!(small.schema_version(), 1);
+
+        let large = UpdateClientPreferenceRequest {
+            schema_version: Some(2),
+            value: json!({ "notes": "x".repeat(MAX_CLIENT_PREFERENCE_BYTES) }),
+        };
+        assert!(!large.fits());
+    }
+}
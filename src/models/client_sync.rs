@@ -0,0 +1,146 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Position in a user's change stream. Clients treat the encoded form as opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientSyncCursor {
+    pub changed_at: DateTime<Utc>,
+    pub document_id: Uuid,
+}
+
+impl ClientSyncCursor {
+    pub fn encode(&self) -> String {
+        format!("{}_{}", self.changed_at.to_rfc3339_opts(SecondsFormat::Micros, true), self.document_id)
+    }
+
+    pub fn parse(cursor: &str) -> Option<Self> {
+        let (changed_at, document_id) = cursor.split_once('_')?;
+        Some(Self {
+            changed_at: DateTime::parse_from_rfc3339(changed_at).ok()?.with_timezone(&Utc),
+            document_id: Uuid::parse_str(document_id).ok()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct ClientSyncChangesQuery {
+    /// `cursor` of the previous response; omit to start a full sync
+    pub cursor: Option<String>,
+    /// Mirror only documents with at least one of these labels (comma-separated IDs)
+    pub label_ids: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Document metadata as mirrored by a client. The file itself is fetched on demand
+/// from `/api/documents/{id}/download` when `file_hash` changes.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClientSyncDocument {
+    pub id: Uuid,
+    pub filename: String,
+    pub original_filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    pub file_hash: Option<String>,
+    pub tags: Vec<String>,
+    pub label_ids: Vec<Uuid>,
+    pub ocr_status: Option<String>,
+    pub has_ocr_text: bool,
+    pub created_at: DateTime<Utc>,
+    /// Version of the document; send it back as `base_updated_at` when pushing changes
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TombstoneReason {
+    Deleted,
+    /// The document changed and no longer has any of the mirrored labels
+    OutOfScope,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClientSyncTombstone {
+    pub document_id: Uuid,
+    pub reason: TombstoneReason,
+    pub removed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClientSyncChangesResponse {
+    /// Created or changed documents, to add or replace
+    pub documents: Vec<ClientSyncDocument>,
+    /// Documents to remove from the mirror
+    pub tombstones: Vec<ClientSyncTombstone>,
+    /// Pass as `cursor` on the next request
+    pub cursor: Option<String>,
+    /// More changes are waiting; request again right away
+    pub has_more: bool,
+}
+
+/// Changes a client made to a document while offline
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ClientDocumentChange {
+    pub document_id: Uuid,
+    /// `updated_at` of the document when the client last synced it
+    pub base_updated_at: DateTime<Utc>,
+    /// New display name; only applied if the document is unchanged since `base_updated_at`
+    pub original_filename: Option<String>,
+    #[serde(default)]
+    pub add_label_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub remove_label_ids: Vec<Uuid>,
+    /// Only applied if the document is unchanged since `base_updated_at`
+    #[serde(default)]
+    pub delete: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ClientSyncPushRequest {
+    pub changes: Vec<ClientDocumentChange>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientChangeOutcome {
+    Applied,
+    /// The document changed on the server; label changes were merged but the rename
+    /// or delete was not applied
+    Conflict,
+    /// The document no longer exists on the server
+    Deleted,
+    /// Nothing was applied, see `message`
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClientChangeResult {
+    pub document_id: Uuid,
+    pub outcome: ClientChangeOutcome,
+    pub message: Option<String>,
+    /// The server's document after the change, to replace the client's copy
+    pub document: Option<ClientSyncDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClientSyncPushResponse {
+    pub results: Vec<ClientChangeResult>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = ClientSyncCursor {
+            changed_at: DateTime::parse_from_rfc3339("2025-10-15T08:30:00.123456Z").unwrap().with_timezone(&Utc),
+            document_id: Uuid::new_v4(),
+        };
+
+        assert_eq!(ClientSyncCursor::parse(&cursor.encode()), Some(cursor));
+        assert_eq!(ClientSyncCursor::parse("2025-10-15T08:30:00Z"), None);
+        assert_eq!(ClientSyncCursor::parse("yesterday_abc"), None);
+    }
+}
@@ -0,0 +1,274 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::SharePermission;
+
+const MAX_COLLECTION_NAME_LENGTH: usize = 200;
+const MAX_COLLECTION_DESCRIPTION_LENGTH: usize = 2000;
+/// Documents or subcollections one request may add or reorder
+pub const MAX_COLLECTION_BATCH: usize = 1000;
+
+/// A user-curated, ordered grouping of documents such as a case file or a project
+/// binder. Collections nest; a subcollection belongs to the owner of its parent.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Collection {
+    pub id: Uuid,
+    /// Owner
+    pub user_id: Uuid,
+    /// None for top-level collections
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    pub description: Option<String>,
+    /// Order among the collections of the same parent
+    pub position: i32,
+    pub document_count: i64,
+    pub child_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Collection {
+    /// Filename of the collection's PDF export, e.g. `Case_2024-17.pdf`
+    pub fn export_filename(&self) -> String {
+        let stem: String = self
+            .name
+            .trim()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect();
+        let stem = stem.trim_matches(|c| c == '_' || c == '.');
+        if stem.is_empty() {
+            "collection.pdf".to_string()
+        } else {
+            format!("{}.pdf", stem)
+        }
+    }
+}
+
+/// A collection the user owns or that was shared with them, directly or through one
+/// of its parents
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CollectionListItem {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub collection: Collection,
+    pub owner_username: String,
+    /// What the user may do with it; `delete` for their own collections
+    pub permission: SharePermission,
+}
+
+/// A document of a collection, in the collection's order
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CollectionDocument {
+    pub document_id: Uuid,
+    pub filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    pub position: i32,
+    pub added_by: Option<Uuid>,
+    pub added_at: DateTime<Utc>,
+}
+
+/// A collection with its documents the user can see and its subcollections
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CollectionDetail {
+    #[serde(flatten)]
+    pub collection: Collection,
+    pub permission: SharePermission,
+    pub documents: Vec<CollectionDocument>,
+    pub children: Vec<Collection>,
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_COLLECTION_NAME_LENGTH {
+        return Err(format!("Collection names are limited to {} characters", MAX_COLLECTION_NAME_LENGTH));
+    }
+    Ok(())
+}
+
+fn validate_description(description: Option<&str>) -> Result<(), String> {
+    if description.is_some_and(|d| d.chars().count() > MAX_COLLECTION_DESCRIPTION_LENGTH) {
+        return Err(format!(
+            "Collection descriptions are limited to {} characters",
+            MAX_COLLECTION_DESCRIPTION_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+fn validate_ids(ids: &[Uuid], what: &str) -> Result<(), String> {
+    if ids.len() > MAX_COLLECTION_BATCH {
+        return Err(format!("At most {} {} per request", MAX_COLLECTION_BATCH, what));
+    }
+    let mut seen = HashSet::with_capacity(ids.len());
+    if let Some(duplicate) = ids.iter().find(|id| !seen.insert(**id)) {
+        return Err(format!("{} is listed twice", duplicate));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateCollectionRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// Create it inside this collection, which needs edit access
+    pub parent_id: Option<Uuid>,
+}
+
+impl CreateCollectionRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name)?;
+        validate_description(self.description.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateCollectionRequest {
+    pub name: Option<String>,
+    /// An empty description removes it
+    pub description: Option<String>,
+}
+
+impl UpdateCollectionRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        validate_description(self.description.as_deref())
+    }
+}
+
+/// Move a collection into another collection of the same owner; `parent_id` of null
+/// makes it a top-level collection
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct MoveCollectionRequest {
+    pub parent_id: Option<Uuid>,
+}
+
+/// Documents to append to a collection, in this order. Documents already in it keep
+/// their place.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CollectionDocumentsRequest {
+    pub document_ids: Vec<Uuid>,
+}
+
+impl CollectionDocumentsRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.document_ids.is_empty() {
+            return Err("No documents given".to_string());
+        }
+        validate_ids(&self.document_ids, "documents")
+    }
+}
+
+/// New order of a collection's documents and subcollections. The listed ones come
+/// first, in the given order; the rest follow in their current order.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct ReorderCollectionRequest {
+    #[serde(default)]
+    pub document_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub collection_ids: Vec<Uuid>,
+}
+
+impl ReorderCollectionRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_ids(&self.document_ids, "documents")?;
+        validate_ids(&self.collection_ids, "collections")
+    }
+}
+
+/// The documents of a collection tree in reading order: a collection's own documents
+/// first, then those of each subcollection in turn. A document found in several
+/// collections is listed where it comes first. `children` and `documents` are keyed
+/// by collection and already ordered by position.
+pub fn depth_first_documents(
+    root_id: Uuid,
+    children: &HashMap<Uuid, Vec<Uuid>>,
+    documents: &HashMap<Uuid, Vec<Uuid>>,
+) -> Vec<Uuid> {
+    let mut ordered = Vec::new();
+    let mut seen_documents = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![root_id];
+    while let Some(collection_id) = stack.pop() {
+        // Guards against a cycle that slipped past the move checks
+        if !visited.insert(collection_id) {
+            continue;
+        }
+        for document_id in documents.get(&collection_id).into_iter().flatten() {
+            if seen_documents.insert(*document_id) {
+                ordered.push(*document_id);
+            }
+        }
+        if let Some(child_ids) = children.get(&collection_id) {
+            stack.extend(child_ids.iter().rev());
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_validation() {
+        let create = CreateCollectionRequest { name: "  ".to_string(), description: None, parent_id: None };
+        assert!(create.validate().is_err());
+
+        let update = UpdateCollectionRequest { name: Some("Case 2024-17".to_string()), description: None };
+        assert!(update.validate().is_ok());
+
+        let document = Uuid::new_v4();
+        let reorder = ReorderCollectionRequest { document_ids: vec![document, document], collection_ids: vec![] };
+        assert!(reorder.validate().is_err());
+        assert!(CollectionDocumentsRequest { document_ids: vec![] }.validate().is_err());
+    }
+
+    #[test]
+    fn test_export_filename() {
+        let collection = |name: &str| Collection {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            parent_id: None,
+            name: name.to_string(),
+            description: None,
+            position: 0,
+            document_count: 0,
+            child_count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert_eq!(collection("Case 2024-17").export_filename(), "Case_2024-17.pdf");
+        assert_eq!(collection("\"Smith\" / Jones").export_filename(), "Smith____Jones.pdf");
+        assert_eq!(collection("Квартира").export_filename(), "collection.pdf");
+    }
+
+    #[test]
+    fn test_depth_first_documents() {
+        let (root, evidence, letters, nested) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let docs: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        let children = HashMap::from([(root, vec![evidence, letters]), (evidence, vec![nested])]);
+        let documents = HashMap::from([
+            (root, vec![docs[0]]),
+            (evidence, vec![docs[1]]),
+            (nested, vec![docs[2], docs[0]]),
+            (letters, vec![docs[3], docs[4]]),
+        ]);
+
+        assert_eq!(
+            depth_first_documents(root, &children, &documents),
+            vec![docs[0], docs[1], docs[2], docs[3], docs[4]]
+        );
+        assert_eq!(depth_first_documents(letters, &children, &documents), vec![docs[3], docs[4]]);
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Kind of inconsistency between the database and storage
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+pub enum ConsistencyIssueKind {
+    /// A document whose stored file no longer exists
+    #[serde(rename = "missing_file")]
+    MissingFile,
+    /// A file in the upload directory that no document refers to
+    #[serde(rename = "orphaned_file")]
+    OrphanedFile,
+    /// A knowledge graph edge pointing at a node of another (or no) document
+    #[serde(rename = "dangling_graph_edge")]
+    DanglingGraphEdge,
+    /// An OCR queue entry for a document that no longer exists
+    #[serde(rename = "orphaned_queue_entry")]
+    OrphanedQueueEntry,
+    /// A document whose stored file no longer matches the hash recorded at ingestion
+    #[serde(rename = "hash_mismatch")]
+    HashMismatch,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConsistencyIssue {
+    pub kind: ConsistencyIssueKind,
+    pub document_id: Option<Uuid>,
+    /// Queue entry or graph edge id
+    pub record_id: Option<Uuid>,
+    pub path: Option<String>,
+    pub detail: String,
+    /// Whether auto-repair fixed this issue
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConsistencyReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Whether the check ran in auto-repair mode
+    pub repair: bool,
+    pub documents_checked: i64,
+    pub files_checked: i64,
+    /// Stored files read back and compared with their recorded hash
+    #[serde(default)]
+    pub hashes_checked: i64,
+    pub issues: Vec<ConsistencyIssue>,
+    pub repaired_count: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ConsistencyCheckRequest {
+    /// Repair the issues found instead of only reporting them
+    #[serde(default)]
+    pub repair: bool,
+    /// Read every stored file back and compare it with the hash recorded at ingestion
+    #[serde(default)]
+    pub verify_hashes: bool,
+}
+
+/// Fix for a single issue of a consistency report
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyRepairAction {
+    /// Ingest an orphaned file as a new document, or accept the changed file of a
+    /// document with a hash mismatch and run OCR on it again
+    Reingest,
+    /// Flag a document whose file is gone so it is no longer offered for OCR or download
+    MarkMissing,
+    /// Delete a stored file that no document refers to
+    DeleteOrphan,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConsistencyRepairRequest {
+    pub action: ConsistencyRepairAction,
+    /// Document of a missing file or hash mismatch
+    pub document_id: Option<Uuid>,
+    /// Path of an orphaned file, as given in the report
+    pub path: Option<String>,
+    /// Owner of the document created by re-ingesting an orphaned file; defaults to
+    /// the admin making the request
+    pub user_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConsistencyRepairResult {
+    pub action: ConsistencyRepairAction,
+    /// Document repaired, or created by re-ingesting an orphaned file
+    pub document_id: Option<Uuid>,
+    pub path: Option<String>,
+    pub detail: String,
+}
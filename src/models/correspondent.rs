@@ -0,0 +1,200 @@
+use chrono::{DateTime, Utc};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// How a correspondent's rule matches document text, see the `correspondents` migration
+pub const MATCH_ALGORITHMS: [&str; 6] = ["none", "auto", "any", "all", "exact", "regex"];
+
+/// Algorithms that need a `match_pattern`
+pub const PATTERN_ALGORITHMS: [&str; 4] = ["any", "all", "exact", "regex"];
+
+/// A sender or recipient of documents, such as a company, an authority or a person
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Correspondent {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// `none`, `auto` (the name occurs in the text), `any`/`all` (words of the
+    /// pattern occur), `exact` (the pattern occurs as a phrase) or `regex`
+    pub match_algorithm: String,
+    pub match_pattern: Option<String>,
+    pub case_sensitive: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CorrespondentWithCount {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub correspondent: Correspondent,
+    pub document_count: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateCorrespondentRequest {
+    pub name: String,
+    /// Default `auto`
+    pub match_algorithm: Option<String>,
+    pub match_pattern: Option<String>,
+    pub case_sensitive: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateCorrespondentRequest {
+    pub name: Option<String>,
+    pub match_algorithm: Option<String>,
+    pub match_pattern: Option<String>,
+    pub case_sensitive: Option<bool>,
+}
+
+/// Set the correspondent of documents; `correspondent_id` of null clears it
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AssignCorrespondentRequest {
+    pub document_ids: Vec<Uuid>,
+    pub correspondent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AssignCorrespondentResponse {
+    pub updated: i64,
+}
+
+/// Result of running the assignment rules over documents without a correspondent
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MatchCorrespondentsResponse {
+    pub documents_checked: i64,
+    pub documents_assigned: i64,
+}
+
+/// A document's correspondent and how it was assigned
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentCorrespondent {
+    pub document_id: Uuid,
+    pub correspondent_id: Uuid,
+    pub name: String,
+    /// `manual`, `rule`, `detected` or `llm`
+    pub assigned_by: String,
+    pub assigned_at: DateTime<Utc>,
+}
+
+impl Correspondent {
+    /// Whether the rule of a `any`, `all`, `exact` or `regex` correspondent matches
+    /// the text. `auto` and `none` never match here; see `name_occurs_in`.
+    pub fn rule_matches(&self, text: &str) -> bool {
+        let Some(pattern) = self.match_pattern.as_deref().filter(|p| !p.trim().is_empty()) else {
+            return false;
+        };
+        match self.match_algorithm.as_str() {
+            "regex" => RegexBuilder::new(pattern)
+                .case_insensitive(!self.case_sensitive)
+                .build()
+                .is_ok_and(|regex| regex.is_match(text)),
+            algorithm => {
+                let (text, pattern) = if self.case_sensitive {
+                    (text.to_string(), pattern.to_string())
+                } else {
+                    (text.to_lowercase(), pattern.to_lowercase())
+                };
+                match algorithm {
+                    "any" => pattern.split_whitespace().any(|word| contains_words(&text, word)),
+                    "all" => pattern.split_whitespace().all(|word| contains_words(&text, word)),
+                    "exact" => contains_words(&text, pattern.trim()),
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    /// Whether the correspondent's name occurs in the text as whole words
+    pub fn name_occurs_in(&self, text: &str) -> bool {
+        if self.case_sensitive {
+            contains_words(text, self.name.trim())
+        } else {
+            contains_words(&text.to_lowercase(), &self.name.trim().to_lowercase())
+        }
+    }
+}
+
+/// Checks a match algorithm and its pattern, returning what is wrong with them
+pub fn validate_match_rule(algorithm: &str, pattern: Option<&str>) -> Result<(), String> {
+    if !MATCH_ALGORITHMS.contains(&algorithm) {
+        return Err(format!("match_algorithm must be one of {}", MATCH_ALGORITHMS.join(", ")));
+    }
+    let pattern = pattern.map(str::trim).filter(|p| !p.is_empty());
+    if PATTERN_ALGORITHMS.contains(&algorithm) && pattern.is_none() {
+        return Err(format!("match_algorithm '{}' needs a match_pattern", algorithm));
+    }
+    if algorithm == "regex" {
+        if let Some(pattern) = pattern {
+            RegexBuilder::new(pattern)
+                .size_limit(1 << 20)
+                .build()
+                .map_err(|e| format!("Invalid regular expression: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `needle` occurs in `haystack` with no letter or digit directly before or after
+pub(crate) fn contains_words(haystack: &str, needle: &str) -> bool {
+    if needle.is_empty() {
+        return false;
+    }
+    haystack.match_indices(needle).any(|(start, _)| {
+        let end = start + needle.len();
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[end..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn correspondent(name: &str, algorithm: &str, pattern: Option<&str>) -> Correspondent {
+        Correspondent {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            name: name.to_string(),
+            match_algorithm: algorithm.to_string(),
+            match_pattern: pattern.map(str::to_string),
+            case_sensitive: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let text = "ACME Corp\nInvoice 2024-113\nTotal due: 120.00 EUR";
+
+        assert!(correspondent("ACME", "any", Some("globex acme")).rule_matches(text));
+        assert!(!correspondent("ACME", "all", Some("globex acme")).rule_matches(text));
+        assert!(correspondent("ACME", "all", Some("invoice acme")).rule_matches(text));
+        assert!(correspondent("ACME", "exact", Some("total due")).rule_matches(text));
+        assert!(!correspondent("ACME", "exact", Some("total d")).rule_matches(text));
+        assert!(correspondent("ACME", "regex", Some(r"invoice \d{4}-\d+")).rule_matches(text));
+        assert!(!correspondent("ACME", "auto", Some("acme")).rule_matches(text));
+        assert!(!correspondent("ACME", "none", Some("acme")).rule_matches(text));
+    }
+
+    #[test]
+    fn test_name_occurs_in_whole_words() {
+        let acme = correspondent("Acme Corp", "auto", None);
+        assert!(acme.name_occurs_in("Invoice from ACME CORP."));
+        assert!(!acme.name_occurs_in("Invoice from Acme Corporation"));
+    }
+
+    #[test]
+    fn test_validate_match_rule() {
+        assert!(validate_match_rule("auto", None).is_ok());
+        assert!(validate_match_rule("any", None).is_err());
+        assert!(validate_match_rule("regex", Some("(unclosed")).is_err());
+        assert!(validate_match_rule("fuzzy", Some("acme")).is_err());
+    }
+}
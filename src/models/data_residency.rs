@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A kind of external service readur can send document content to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentDestination {
+    /// The chat and vision model: analyses, titles, vision OCR and LLM translations
+    Llm,
+    /// A LibreTranslate-compatible translation API
+    Translation,
+    /// The text-to-speech API documents are narrated with
+    TextToSpeech,
+    /// Meilisearch or Typesense, which index OCR text
+    SearchEngine,
+    /// Ingestion hook webhooks, sent new files and the details of processed documents
+    IngestionHook,
+}
+
+impl ContentDestination {
+    pub const ALL: [ContentDestination; 5] = [
+        ContentDestination::Llm,
+        ContentDestination::Translation,
+        ContentDestination::TextToSpeech,
+        ContentDestination::SearchEngine,
+        ContentDestination::IngestionHook,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentDestination::Llm => "llm",
+            ContentDestination::Translation => "translation",
+            ContentDestination::TextToSpeech => "text_to_speech",
+            ContentDestination::SearchEngine => "search_engine",
+            ContentDestination::IngestionHook => "ingestion_hook",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::ALL.into_iter().find(|destination| destination.as_str() == name)
+    }
+}
+
+/// How strictly document content is kept in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DataResidencyMode {
+    /// Content may go to any configured service
+    Open,
+    /// Content may only go to the allowed hosts
+    Allowlist,
+    /// No content leaves the instance
+    Offline,
+}
+
+/// Which services may receive document content, from `DATA_RESIDENCY_MODE`,
+/// `DATA_RESIDENCY_ALLOWED_HOSTS` and `DATA_RESIDENCY_ALLOWED_SERVICES`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DataResidencyPolicy {
+    pub mode: DataResidencyMode,
+    /// Hosts content may be sent to in allowlist mode; `*.example.com` allows the
+    /// subdomains of example.com
+    pub allowed_hosts: Vec<String>,
+    /// Kinds of service content may be sent to, in any mode but offline
+    pub allowed_destinations: Vec<ContentDestination>,
+}
+
+impl DataResidencyPolicy {
+    /// The policy from the environment variables `lookup` returns. A mode that cannot
+    /// be read is taken as offline, so a typo never lets content out.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mode = match lookup("DATA_RESIDENCY_MODE").map(|v| v.trim().to_lowercase()).as_deref() {
+            None | Some("") | Some("open") => DataResidencyMode::Open,
+            Some("allowlist") => DataResidencyMode::Allowlist,
+            Some("offline") => DataResidencyMode::Offline,
+            Some(other) => {
+                tracing::error!("Unknown DATA_RESIDENCY_MODE '{}', keeping all content in (offline)", other);
+                DataResidencyMode::Offline
+            }
+        };
+        let list = |name: &str| -> Vec<String> {
+            lookup(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(|item| item.trim().to_lowercase())
+                .filter(|item| !item.is_empty())
+                .collect()
+        };
+
+        let services = list("DATA_RESIDENCY_ALLOWED_SERVICES");
+        let allowed_destinations = if services.is_empty() {
+            ContentDestination::ALL.to_vec()
+        } else {
+            services
+                .iter()
+                .filter_map(|name| {
+                    let destination = ContentDestination::parse(name);
+                    if destination.is_none() {
+                        tracing::warn!("Ignoring unknown service '{}' in DATA_RESIDENCY_ALLOWED_SERVICES", name);
+                    }
+                    destination
+                })
+                .collect()
+        };
+
+        DataResidencyPolicy { mode, allowed_hosts: list("DATA_RESIDENCY_ALLOWED_HOSTS"), allowed_destinations }
+    }
+
+    /// Whether content may be sent to the service at `url`; the reason when not
+    pub fn check(&self, destination: ContentDestination, url: &str) -> Result<(), String> {
+        if self.mode == DataResidencyMode::Offline {
+            return Err("the instance is offline".to_string());
+        }
+        if !self.allowed_destinations.contains(&destination) {
+            return Err(format!("{} is not an allowed service", destination.as_str()));
+        }
+        if self.mode == DataResidencyMode::Allowlist {
+            let host = url_host(url).ok_or_else(|| "the service's address has no host".to_string())?;
+            if !self.allowed_hosts.iter().any(|allowed| host_matches(allowed, &host)) {
+                return Err(format!("{} is not an allowed host", host));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lowercase host of a URL, without port or credentials
+pub fn url_host(url: &str) -> Option<String> {
+    url::Url::parse(url.trim()).ok()?.host_str().map(|host| host.trim_matches(['[', ']']).to_lowercase())
+}
+
+fn host_matches(allowed: &str, host: &str) -> bool {
+    match allowed.strip_prefix("*.") {
+        Some(domain) => host.strip_suffix(domain).is_some_and(|sub| sub.ends_with('.') && sub.len() > 1),
+        None => allowed.trim_matches(['[', ']']) == host,
+    }
+}
+
+/// A configured service content can be sent to and whether the policy lets it through
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContentDestinationStatus {
+    pub destination: ContentDestination,
+    /// Name of the ingestion hook
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub host: Option<String>,
+    pub allowed: bool,
+    /// Why content is not let through
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DataResidencyStatus {
+    pub policy: DataResidencyPolicy,
+    /// The services configured on this instance
+    pub destinations: Vec<ContentDestinationStatus>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(vars: &[(&str, &str)]) -> DataResidencyPolicy {
+        DataResidencyPolicy::from_lookup(|name| {
+            vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_modes() {
+        let open = policy(&[]);
+        assert_eq!(open.mode, DataResidencyMode::Open);
+        assert!(open.check(ContentDestination::Llm, "https://api.openai.com/v1/chat/completions").is_ok());
+
+        let offline = policy(&[("DATA_RESIDENCY_MODE", "offline")]);
+        assert!(offline.check(ContentDestination::Llm, "http://localhost:11434/v1").is_err());
+
+        // Unreadable modes fail closed
+        assert_eq!(policy(&[("DATA_RESIDENCY_MODE", "ofline")]).mode, DataResidencyMode::Offline);
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let allowlist = policy(&[
+            ("DATA_RESIDENCY_MODE", "allowlist"),
+            ("DATA_RESIDENCY_ALLOWED_HOSTS", "localhost, *.corp.example, [::1]"),
+        ]);
+        assert!(allowlist.check(ContentDestination::Llm, "http://localhost:11434/v1/chat/completions").is_ok());
+        assert!(allowlist.check(ContentDestination::Translation, "https://mt.corp.example/translate").is_ok());
+        assert!(allowlist.check(ContentDestination::Llm, "http://[::1]:8080/v1").is_ok());
+        assert!(allowlist.check(ContentDestination::Llm, "https://corp.example/v1").is_err());
+        assert!(allowlist.check(ContentDestination::Llm, "https://evilcorp.example/v1").is_err());
+        assert!(allowlist.check(ContentDestination::Llm, "https://api.openai.com/v1").is_err());
+        assert!(allowlist.check(ContentDestination::Llm, "not a url").is_err());
+    }
+
+    #[test]
+    fn test_allowed_services() {
+        let policy = policy(&[("DATA_RESIDENCY_ALLOWED_SERVICES", "search_engine,bogus")]);
+        assert_eq!(policy.allowed_destinations, vec![ContentDestination::SearchEngine]);
+        assert!(policy.check(ContentDestination::SearchEngine, "http://meilisearch:7700").is_ok());
+        assert_eq!(
+            policy.check(ContentDestination::Llm, "http://localhost"),
+            Err("llm is not an allowed service".to_string())
+        );
+    }
+}
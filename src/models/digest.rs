@@ -0,0 +1,241 @@
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Default hour (UTC) digests go out
+pub const DEFAULT_DIGEST_HOUR: i16 = 7;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFrequency {
+    #[default]
+    Off,
+    Daily,
+    Weekly,
+}
+
+impl std::fmt::Display for DigestFrequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DigestFrequency::Off => write!(f, "off"),
+            DigestFrequency::Daily => write!(f, "daily"),
+            DigestFrequency::Weekly => write!(f, "weekly"),
+        }
+    }
+}
+
+impl TryFrom<String> for DigestFrequency {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "off" => Ok(DigestFrequency::Off),
+            "daily" => Ok(DigestFrequency::Daily),
+            "weekly" => Ok(DigestFrequency::Weekly),
+            _ => Err(format!("Unknown digest frequency: {}", value)),
+        }
+    }
+}
+
+impl DigestFrequency {
+    /// Time between two digests
+    pub fn period(self) -> Option<Duration> {
+        match self {
+            DigestFrequency::Off => None,
+            DigestFrequency::Daily => Some(Duration::days(1)),
+            DigestFrequency::Weekly => Some(Duration::weeks(1)),
+        }
+    }
+}
+
+/// When a user's digest goes out
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DigestSettings {
+    pub user_id: Uuid,
+    #[sqlx(try_from = "String")]
+    pub frequency: DigestFrequency,
+    /// Hour of the day (UTC), 0-23
+    pub send_hour: i16,
+    /// Day of weekly digests, 0 = Monday to 6 = Sunday
+    pub weekday: i16,
+    /// Send nothing when there is nothing to report
+    pub skip_empty: bool,
+    /// End of the period the latest digest covered
+    pub last_sent_at: Option<DateTime<Utc>>,
+    /// Why the latest digest could not be sent, cleared when one is
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DigestSettings {
+    /// Settings of a user who never saved any: no digest
+    pub fn default_for(user_id: Uuid, now: DateTime<Utc>) -> Self {
+        Self {
+            user_id,
+            frequency: DigestFrequency::Off,
+            send_hour: DEFAULT_DIGEST_HOUR,
+            weekday: 0,
+            skip_empty: true,
+            last_sent_at: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// The latest time a digest was scheduled for, up to `now`
+    pub fn latest_schedule(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let time = NaiveTime::from_hms_opt(self.send_hour as u32, 0, 0)?;
+        let mut scheduled = now.date_naive().and_time(time).and_utc();
+        if scheduled > now {
+            scheduled -= Duration::days(1);
+        }
+        match self.frequency {
+            DigestFrequency::Off => None,
+            DigestFrequency::Daily => Some(scheduled),
+            DigestFrequency::Weekly => {
+                let days_since = (scheduled.weekday().num_days_from_monday() as i64 - self.weekday as i64).rem_euclid(7);
+                Some(scheduled - Duration::days(days_since))
+            }
+        }
+    }
+
+    /// Whether a digest was scheduled since the previous one went out. Settings saved
+    /// after a scheduled time wait for the next one.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        let previous = self.last_sent_at.map_or(self.updated_at, |sent| sent.max(self.updated_at));
+        self.latest_schedule(now).is_some_and(|scheduled| scheduled > previous)
+    }
+
+    /// Start of the period a digest sent at `now` covers
+    pub fn period_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.last_sent_at
+            .unwrap_or_else(|| now - self.frequency.period().unwrap_or(Duration::days(1)))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateDigestSettingsRequest {
+    pub frequency: Option<DigestFrequency>,
+    pub send_hour: Option<i16>,
+    pub weekday: Option<i16>,
+    pub skip_empty: Option<bool>,
+}
+
+impl UpdateDigestSettingsRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.send_hour.is_some_and(|hour| !(0..=23).contains(&hour)) {
+            return Err("send_hour must be between 0 and 23".to_string());
+        }
+        if self.weekday.is_some_and(|weekday| !(0..=6).contains(&weekday)) {
+            return Err("weekday must be between 0 (Monday) and 6 (Sunday)".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A document ingested during a digest's period
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DigestDocument {
+    pub id: Uuid,
+    pub original_filename: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A file that failed to be ingested or processed during a digest's period
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DigestFailure {
+    pub id: Uuid,
+    pub filename: String,
+    pub failure_reason: String,
+    pub failure_stage: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A saved search that matched new documents during a digest's period
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DigestSearchMatch {
+    pub saved_search_id: Uuid,
+    pub name: String,
+    pub matches: i64,
+}
+
+/// What a digest reports. The lists hold the most recent entries; the counts cover
+/// the whole period.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Digest {
+    pub user_id: Uuid,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub new_document_count: i64,
+    pub new_documents: Vec<DigestDocument>,
+    pub failure_count: i64,
+    pub failures: Vec<DigestFailure>,
+    pub search_matches: Vec<DigestSearchMatch>,
+}
+
+impl Digest {
+    pub fn is_empty(&self) -> bool {
+        self.new_document_count == 0 && self.failure_count == 0 && self.search_matches.is_empty()
+    }
+}
+
+/// A digest as it would be emailed now
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DigestPreview {
+    pub subject: String,
+    pub body: String,
+    pub digest: Digest,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        // October 2025; the 13th is a Monday
+        Utc.with_ymd_and_hms(2025, 10, day, hour, minute, 0).unwrap()
+    }
+
+    fn settings(frequency: DigestFrequency, last_sent_at: Option<DateTime<Utc>>) -> DigestSettings {
+        DigestSettings {
+            frequency,
+            send_hour: 7,
+            weekday: 2,
+            last_sent_at,
+            ..DigestSettings::default_for(Uuid::new_v4(), at(1, 0, 0))
+        }
+    }
+
+    #[test]
+    fn test_latest_schedule() {
+        let daily = settings(DigestFrequency::Daily, None);
+        assert_eq!(daily.latest_schedule(at(15, 6, 59)), Some(at(14, 7, 0)));
+        assert_eq!(daily.latest_schedule(at(15, 7, 0)), Some(at(15, 7, 0)));
+
+        // Wednesdays
+        let weekly = settings(DigestFrequency::Weekly, None);
+        assert_eq!(weekly.latest_schedule(at(15, 8, 0)), Some(at(15, 7, 0)));
+        assert_eq!(weekly.latest_schedule(at(15, 6, 0)), Some(at(8, 7, 0)));
+        assert_eq!(weekly.latest_schedule(at(20, 12, 0)), Some(at(15, 7, 0)));
+
+        assert_eq!(settings(DigestFrequency::Off, None).latest_schedule(at(15, 8, 0)), None);
+    }
+
+    #[test]
+    fn test_is_due() {
+        let daily = settings(DigestFrequency::Daily, Some(at(14, 7, 0)));
+        assert!(!daily.is_due(at(15, 6, 0)));
+        assert!(daily.is_due(at(15, 7, 5)));
+        assert!(!settings(DigestFrequency::Daily, Some(at(15, 7, 5))).is_due(at(15, 9, 0)));
+
+        // Enabled after today's time: nothing until tomorrow
+        let enabled_late = DigestSettings { updated_at: at(15, 8, 0), ..settings(DigestFrequency::Daily, None) };
+        assert!(!enabled_late.is_due(at(15, 9, 0)));
+        assert!(enabled_late.is_due(at(16, 7, 0)));
+    }
+}
@@ -175,7 +175,7 @@ pub struct FailedDocument {
     pub failure_reason: String,
     /// Stage at which the document failed
     pub failure_stage: String,
-    /// Reference to existing document if failed due to duplicate
+    /// The document a duplicate duplicates, or the document whose OCR failed
     pub existing_document_id: Option<Uuid>,
     /// Source of the ingestion attempt
     pub ingestion_source: String,
@@ -0,0 +1,179 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use super::{StatisticsBucket, StatisticsQuery};
+
+/// Age a document must reach before it counts as unused when a request gives none
+pub const DEFAULT_UNUSED_AFTER_DAYS: i64 = 90;
+pub const DEFAULT_UNUSED_DOCUMENTS_LIMIT: i64 = 50;
+pub const MAX_UNUSED_DOCUMENTS_LIMIT: i64 = 500;
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct AccessStatisticsQuery {
+    /// First day to include (default: 30 days before `to`)
+    pub from: Option<NaiveDate>,
+    /// Last day to include (default: today, UTC)
+    pub to: Option<NaiveDate>,
+    /// Bucket length of the activity series (default: day)
+    pub bucket: Option<StatisticsBucket>,
+    /// Only documents of this user
+    pub user_id: Option<Uuid>,
+    /// Only this document, e.g. for its own heatmap
+    pub document_id: Option<Uuid>,
+    /// Documents listed (default 10, at most 100)
+    pub top: Option<i64>,
+}
+
+impl AccessStatisticsQuery {
+    fn as_statistics_query(&self) -> StatisticsQuery {
+        StatisticsQuery {
+            from: self.from,
+            to: self.to,
+            bucket: self.bucket,
+            user_id: self.user_id,
+            top: self.top,
+        }
+    }
+
+    /// The days covered and the bucket length, or why they are not acceptable
+    pub fn range(&self, today: NaiveDate) -> Result<(NaiveDate, NaiveDate, StatisticsBucket), String> {
+        self.as_statistics_query().range(today)
+    }
+
+    pub fn top(&self) -> i64 {
+        self.as_statistics_query().top()
+    }
+}
+
+/// Views and downloads in one bucket
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AccessBucket {
+    /// First day of the bucket
+    pub start: NaiveDate,
+    pub views: i64,
+    pub downloads: i64,
+    /// Documents opened at least once
+    pub documents: i64,
+    /// Signed-in users who opened any; visitors of share links and guest portals are not counted
+    pub users: i64,
+}
+
+/// A document and how often it was opened in the range
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AccessedDocument {
+    pub document_id: Uuid,
+    pub filename: String,
+    pub owner_id: Uuid,
+    pub owner_username: String,
+    pub views: i64,
+    pub downloads: i64,
+    pub users: i64,
+    /// Last day it was opened, at any time
+    pub last_accessed: Option<NaiveDate>,
+}
+
+/// What one user opened in the range
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserAccessStatistics {
+    pub user_id: Uuid,
+    pub username: String,
+    pub views: i64,
+    pub downloads: i64,
+    /// Distinct documents opened
+    pub documents: i64,
+    /// Last day they opened any document, at any time
+    pub last_active: Option<NaiveDate>,
+}
+
+/// How the library is used over the range
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccessStatistics {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub bucket: StatisticsBucket,
+    pub user_id: Option<Uuid>,
+    pub document_id: Option<Uuid>,
+    /// Views and downloads per bucket, empty buckets included
+    pub activity: Vec<AccessBucket>,
+    /// Most opened first
+    pub most_accessed: Vec<AccessedDocument>,
+    /// Per user, most views first; users who opened nothing are left out
+    pub users: Vec<UserAccessStatistics>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct UnusedDocumentsQuery {
+    /// Only documents uploaded at least this many days ago (default 90)
+    pub older_than_days: Option<i64>,
+    /// Only documents of this user
+    pub user_id: Option<Uuid>,
+    /// Default 50, at most 500
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl UnusedDocumentsQuery {
+    /// Documents uploaded before this time qualify
+    pub fn uploaded_before(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::days(self.older_than_days.unwrap_or(DEFAULT_UNUSED_AFTER_DAYS).clamp(0, 36_600))
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_UNUSED_DOCUMENTS_LIMIT).clamp(1, MAX_UNUSED_DOCUMENTS_LIMIT)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+/// A document nobody opened since it was uploaded
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UnusedDocument {
+    pub document_id: Uuid,
+    pub filename: String,
+    pub owner_id: Uuid,
+    pub owner_username: String,
+    pub file_size: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UnusedDocumentsResponse {
+    pub documents: Vec<UnusedDocument>,
+    pub total: i64,
+    /// Bytes the unused documents take, all of them rather than this page
+    pub total_bytes: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unused_documents_query_defaults() {
+        let now = Utc::now();
+        let query = UnusedDocumentsQuery::default();
+        assert_eq!(query.uploaded_before(now), now - chrono::Duration::days(DEFAULT_UNUSED_AFTER_DAYS));
+        assert_eq!(query.limit(), DEFAULT_UNUSED_DOCUMENTS_LIMIT);
+        assert_eq!(query.offset(), 0);
+
+        let query = UnusedDocumentsQuery { older_than_days: Some(-5), limit: Some(10_000), offset: Some(-1), user_id: None };
+        assert_eq!(query.uploaded_before(now), now);
+        assert_eq!(query.limit(), MAX_UNUSED_DOCUMENTS_LIMIT);
+        assert_eq!(query.offset(), 0);
+    }
+
+    #[test]
+    fn test_access_query_uses_statistics_range() {
+        let today = NaiveDate::from_ymd_opt(2025, 10, 15).unwrap();
+        let query = AccessStatisticsQuery { top: Some(1_000), ..Default::default() };
+        let (from, to, bucket) = query.range(today).unwrap();
+        assert_eq!((to - from).num_days(), 29);
+        assert_eq!(bucket, StatisticsBucket::Day);
+        assert_eq!(query.top(), 100);
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// The document stops being valid on the date
+pub const EXPIRATION_KIND_EXPIRATION: &str = "expiration";
+/// The document, e.g. a contract, renews on the date unless cancelled before
+pub const EXPIRATION_KIND_RENEWAL: &str = "renewal";
+pub const EXPIRATION_KINDS: [&str; 2] = [EXPIRATION_KIND_EXPIRATION, EXPIRATION_KIND_RENEWAL];
+pub const EXPIRATION_SOURCE_MANUAL: &str = "manual";
+pub const EXPIRATION_SOURCE_EXTRACTED: &str = "extracted";
+
+pub const MAX_NOTICE_DAYS: i32 = 365;
+const MAX_EXPIRATION_NOTE_LENGTH: usize = 1000;
+const DEFAULT_UPCOMING_DAYS: i64 = 90;
+const MAX_UPCOMING_DAYS: i64 = 3650;
+
+/// When a document expires or renews, and when its owner is told
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentExpiration {
+    pub document_id: Uuid,
+    pub document_filename: String,
+    /// Owner of the document, who is notified
+    pub user_id: Uuid,
+    pub expires_on: NaiveDate,
+    /// `expiration` or `renewal`
+    pub kind: String,
+    /// Days before `expires_on` the owner is notified
+    pub notice_days: i32,
+    pub note: Option<String>,
+    /// `manual`, or `extracted` from the OCR text
+    pub source: String,
+    pub notified_at: Option<DateTime<Utc>>,
+    /// Days until `expires_on`, negative once it passed
+    pub days_left: i32,
+    pub set_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Set when a document expires or renews. A new date or notice period sends the
+/// notification again when due.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetDocumentExpirationRequest {
+    pub expires_on: NaiveDate,
+    /// `expiration` (default) or `renewal`
+    pub kind: Option<String>,
+    /// Defaults to `EXPIRATION_NOTICE_DAYS`
+    pub notice_days: Option<i32>,
+    /// e.g. "Cancel by registered mail"
+    pub note: Option<String>,
+}
+
+impl SetDocumentExpirationRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(kind) = &self.kind {
+            if !EXPIRATION_KINDS.contains(&kind.as_str()) {
+                return Err(format!("Kind must be one of {}", EXPIRATION_KINDS.join(", ")));
+            }
+        }
+        if self.notice_days.is_some_and(|days| !(0..=MAX_NOTICE_DAYS).contains(&days)) {
+            return Err(format!("Notice must be 0 to {} days", MAX_NOTICE_DAYS));
+        }
+        if self.note.as_ref().is_some_and(|note| note.chars().count() > MAX_EXPIRATION_NOTE_LENGTH) {
+            return Err(format!("Notes are limited to {} characters", MAX_EXPIRATION_NOTE_LENGTH));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct UpcomingExpirationsQuery {
+    /// Documents expiring within this many days, 90 by default
+    pub within_days: Option<i64>,
+    /// Also list documents that already expired
+    #[serde(default)]
+    pub include_expired: bool,
+    /// Only `expiration` or `renewal` dates
+    pub kind: Option<String>,
+}
+
+impl UpcomingExpirationsQuery {
+    pub fn within_days(&self) -> i64 {
+        self.within_days.unwrap_or(DEFAULT_UPCOMING_DAYS).clamp(0, MAX_UPCOMING_DAYS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> SetDocumentExpirationRequest {
+        SetDocumentExpirationRequest {
+            expires_on: NaiveDate::from_ymd_opt(2026, 3, 31).unwrap(),
+            kind: None,
+            notice_days: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(request().validate().is_ok());
+        assert!(SetDocumentExpirationRequest { kind: Some("renewal".to_string()), ..request() }.validate().is_ok());
+        assert!(SetDocumentExpirationRequest { kind: Some("lapse".to_string()), ..request() }.validate().is_err());
+        assert!(SetDocumentExpirationRequest { notice_days: Some(366), ..request() }.validate().is_err());
+        assert!(SetDocumentExpirationRequest { notice_days: Some(-1), ..request() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_within_days() {
+        assert_eq!(UpcomingExpirationsQuery::default().within_days(), 90);
+        let query = UpcomingExpirationsQuery { within_days: Some(100_000), ..Default::default() };
+        assert_eq!(query.within_days(), MAX_UPCOMING_DAYS);
+    }
+}
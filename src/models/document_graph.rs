@@ -0,0 +1,290 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use std::collections::HashSet;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Graph versions kept per document; older ones are removed after each analysis
+pub const MAX_GRAPH_VERSIONS: i32 = 20;
+
+/// One analysis run's knowledge graph of a document
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct GraphVersion {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub version_number: i32,
+    /// Model that extracted the graph; None for restored graphs
+    pub model: Option<String>,
+    pub node_count: i32,
+    pub edge_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentGraphNode {
+    pub id: Uuid,
+    pub label: String,
+    pub name: String,
+    pub properties: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentGraphEdge {
+    pub id: Uuid,
+    #[sqlx(rename = "source_node_id")]
+    pub source: Uuid,
+    #[sqlx(rename = "target_node_id")]
+    pub target: Uuid,
+    pub relationship: String,
+    pub properties: Option<Value>,
+}
+
+/// A document's knowledge graph as users see it: a version with the corrections applied
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentGraph {
+    /// None when the document has not been analyzed
+    pub version: Option<GraphVersion>,
+    pub nodes: Vec<DocumentGraphNode>,
+    pub edges: Vec<DocumentGraphEdge>,
+    /// Corrections that changed this version
+    pub corrections_applied: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphCorrectionKind {
+    RenameNode,
+    DeleteNode,
+    DeleteEdge,
+}
+
+impl std::fmt::Display for GraphCorrectionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphCorrectionKind::RenameNode => write!(f, "rename_node"),
+            GraphCorrectionKind::DeleteNode => write!(f, "delete_node"),
+            GraphCorrectionKind::DeleteEdge => write!(f, "delete_edge"),
+        }
+    }
+}
+
+impl TryFrom<String> for GraphCorrectionKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "rename_node" => Ok(GraphCorrectionKind::RenameNode),
+            "delete_node" => Ok(GraphCorrectionKind::DeleteNode),
+            "delete_edge" => Ok(GraphCorrectionKind::DeleteEdge),
+            _ => Err(format!("Unknown graph correction: {}", value)),
+        }
+    }
+}
+
+/// A correction names entities and relationships rather than node ids, so it applies
+/// to every later analysis that finds them again. Names match ignoring case.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct GraphChange {
+    #[sqlx(try_from = "String")]
+    pub kind: GraphCorrectionKind,
+    /// The node renamed or deleted
+    pub node_label: Option<String>,
+    pub node_name: Option<String>,
+    /// What the node is renamed to
+    pub new_label: Option<String>,
+    pub new_name: Option<String>,
+    /// The edge deleted
+    pub source_name: Option<String>,
+    pub target_name: Option<String>,
+    pub relationship: Option<String>,
+}
+
+impl GraphChange {
+    fn empty(kind: GraphCorrectionKind) -> Self {
+        Self {
+            kind,
+            node_label: None,
+            node_name: None,
+            new_label: None,
+            new_name: None,
+            source_name: None,
+            target_name: None,
+            relationship: None,
+        }
+    }
+
+    pub fn rename_node(node: &DocumentGraphNode, new_label: &str, new_name: &str) -> Self {
+        Self {
+            node_label: Some(node.label.clone()),
+            node_name: Some(node.name.clone()),
+            new_label: Some(new_label.to_string()),
+            new_name: Some(new_name.to_string()),
+            ..Self::empty(GraphCorrectionKind::RenameNode)
+        }
+    }
+
+    pub fn delete_node(node: &DocumentGraphNode) -> Self {
+        Self {
+            node_label: Some(node.label.clone()),
+            node_name: Some(node.name.clone()),
+            ..Self::empty(GraphCorrectionKind::DeleteNode)
+        }
+    }
+
+    pub fn delete_edge(source: &DocumentGraphNode, target: &DocumentGraphNode, relationship: &str) -> Self {
+        Self {
+            source_name: Some(source.name.clone()),
+            target_name: Some(target.name.clone()),
+            relationship: Some(relationship.to_string()),
+            ..Self::empty(GraphCorrectionKind::DeleteEdge)
+        }
+    }
+
+    fn matches_node(&self, node: &DocumentGraphNode) -> bool {
+        self.node_label.as_deref() == Some(node.label.as_str()) && same_name(self.node_name.as_deref(), &node.name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct GraphCorrection {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub change: GraphChange,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RenameGraphNodeRequest {
+    pub name: String,
+    /// New entity type (default: unchanged)
+    pub label: Option<String>,
+}
+
+impl RenameGraphNodeRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Name is required".to_string());
+        }
+        if self.label.as_ref().is_some_and(|label| label.trim().is_empty()) {
+            return Err("Label cannot be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct DocumentGraphQuery {
+    /// Version to return (default: the latest)
+    pub version: Option<i32>,
+}
+
+fn same_name(expected: Option<&str>, name: &str) -> bool {
+    expected.is_some_and(|expected| expected.trim().to_lowercase() == name.trim().to_lowercase())
+}
+
+/// Apply corrections, oldest first, to a graph version. Returns how many of them
+/// changed it.
+pub fn apply_graph_corrections<'a>(
+    nodes: &mut Vec<DocumentGraphNode>,
+    edges: &mut Vec<DocumentGraphEdge>,
+    changes: impl IntoIterator<Item = &'a GraphChange>,
+) -> usize {
+    let mut applied = 0;
+    for change in changes {
+        let changed = match change.kind {
+            GraphCorrectionKind::RenameNode => {
+                let mut renamed = false;
+                for node in nodes.iter_mut().filter(|node| change.matches_node(node)) {
+                    if let Some(label) = &change.new_label {
+                        node.label = label.clone();
+                    }
+                    if let Some(name) = &change.new_name {
+                        node.name = name.clone();
+                    }
+                    renamed = true;
+                }
+                renamed
+            }
+            GraphCorrectionKind::DeleteNode => {
+                let removed: HashSet<Uuid> =
+                    nodes.iter().filter(|node| change.matches_node(node)).map(|node| node.id).collect();
+                nodes.retain(|node| !removed.contains(&node.id));
+                edges.retain(|edge| !removed.contains(&edge.source) && !removed.contains(&edge.target));
+                !removed.is_empty()
+            }
+            GraphCorrectionKind::DeleteEdge => {
+                let name_of = |id: Uuid| nodes.iter().find(|node| node.id == id).map(|node| node.name.as_str());
+                let before = edges.len();
+                edges.retain(|edge| {
+                    let wrong = name_of(edge.source).is_some_and(|name| same_name(change.source_name.as_deref(), name))
+                        && name_of(edge.target).is_some_and(|name| same_name(change.target_name.as_deref(), name))
+                        && same_name(change.relationship.as_deref(), &edge.relationship);
+                    !wrong
+                });
+                edges.len() < before
+            }
+        };
+        if changed {
+            applied += 1;
+        }
+    }
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(label: &str, name: &str) -> DocumentGraphNode {
+        DocumentGraphNode { id: Uuid::new_v4(), label: label.to_string(), name: name.to_string(), properties: None }
+    }
+
+    fn edge(source: &DocumentGraphNode, target: &DocumentGraphNode, relationship: &str) -> DocumentGraphEdge {
+        DocumentGraphEdge {
+            id: Uuid::new_v4(),
+            source: source.id,
+            target: target.id,
+            relationship: relationship.to_string(),
+            properties: None,
+        }
+    }
+
+    #[test]
+    fn test_corrections_apply_to_a_new_analysis() {
+        let corrections = {
+            let john = node("Person", "Jon Doe");
+            let acme = node("Company", "ACME");
+            vec![
+                GraphChange::rename_node(&john, "Person", "John Doe"),
+                GraphChange::delete_edge(&node("Person", "John Doe"), &acme, "OWNS"),
+                GraphChange::delete_node(&node("Company", "Lorem Ipsum")),
+            ]
+        };
+
+        // A later analysis finds the same entities under new node ids
+        let john = node("Person", "jon doe");
+        let acme = node("Company", "ACME");
+        let filler = node("Company", "Lorem Ipsum");
+        let mut nodes = vec![john.clone(), acme.clone(), filler.clone()];
+        let mut edges = vec![edge(&john, &acme, "WORKS_FOR"), edge(&john, &acme, "owns"), edge(&filler, &acme, "PART_OF")];
+
+        assert_eq!(apply_graph_corrections(&mut nodes, &mut edges, &corrections), 3);
+        assert_eq!(nodes.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), vec!["John Doe", "ACME"]);
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].relationship, "WORKS_FOR");
+    }
+
+    #[test]
+    fn test_corrections_for_missing_entities_are_not_applied() {
+        let mut nodes = vec![node("Person", "Jane Roe")];
+        let mut edges = Vec::new();
+        let corrections = [GraphChange::delete_node(&node("Person", "John Doe"))];
+        assert_eq!(apply_graph_corrections(&mut nodes, &mut edges, &corrections), 0);
+        assert_eq!(nodes.len(), 1);
+    }
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// The step of processing a document is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressStage {
+    Ingestion,
+    Ocr,
+    /// The steps after OCR: labels, correspondents, extraction, scans and hooks
+    Analysis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// How far a document is through ingestion, OCR and analysis; the data of
+/// `document.progress` events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DocumentProgress {
+    pub document_id: Uuid,
+    pub stage: ProgressStage,
+    pub status: ProgressStatus,
+    /// Percent of the stage done
+    pub percent: u8,
+    /// Percent of all processing done, across the stages
+    pub overall_percent: u8,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,256 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+const MAX_RELATION_NOTE_LENGTH: usize = 1000;
+
+/// How the source document of a relation relates to its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentRelationType {
+    Related,
+    References,
+    /// e.g. an amendment of a contract
+    Amends,
+    /// A newer version that replaces the target
+    Supersedes,
+    /// e.g. a payment receipt of an invoice
+    PaymentFor,
+    /// A reply to a letter or request
+    RespondsTo,
+    AttachmentOf,
+}
+
+impl std::fmt::Display for DocumentRelationType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentRelationType::Related => write!(f, "related"),
+            DocumentRelationType::References => write!(f, "references"),
+            DocumentRelationType::Amends => write!(f, "amends"),
+            DocumentRelationType::Supersedes => write!(f, "supersedes"),
+            DocumentRelationType::PaymentFor => write!(f, "payment_for"),
+            DocumentRelationType::RespondsTo => write!(f, "responds_to"),
+            DocumentRelationType::AttachmentOf => write!(f, "attachment_of"),
+        }
+    }
+}
+
+impl TryFrom<String> for DocumentRelationType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "related" => Ok(DocumentRelationType::Related),
+            "references" => Ok(DocumentRelationType::References),
+            "amends" => Ok(DocumentRelationType::Amends),
+            "supersedes" => Ok(DocumentRelationType::Supersedes),
+            "payment_for" => Ok(DocumentRelationType::PaymentFor),
+            "responds_to" => Ok(DocumentRelationType::RespondsTo),
+            "attachment_of" => Ok(DocumentRelationType::AttachmentOf),
+            _ => Err(format!("Unknown document relation type: {}", value)),
+        }
+    }
+}
+
+/// Which end of a relation the document it is listed on is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RelationDirection {
+    /// The document is the relation's source
+    Outgoing,
+    /// The document is the relation's target
+    Incoming,
+}
+
+impl TryFrom<String> for RelationDirection {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "outgoing" => Ok(RelationDirection::Outgoing),
+            "incoming" => Ok(RelationDirection::Incoming),
+            _ => Err(format!("Unknown relation direction: {}", value)),
+        }
+    }
+}
+
+/// "<source> <relation_type> <target>", e.g. a receipt that is the payment for an invoice
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentRelation {
+    pub id: Uuid,
+    pub source_document_id: Uuid,
+    pub target_document_id: Uuid,
+    #[sqlx(try_from = "String")]
+    pub relation_type: DocumentRelationType,
+    pub note: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A relation as listed on one of its documents, with the document at the other end
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LinkedDocumentRelation {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub relation: DocumentRelation,
+    #[sqlx(try_from = "String")]
+    pub direction: RelationDirection,
+    /// The document at the other end
+    pub document_id: Uuid,
+    pub filename: String,
+}
+
+fn validate_note(note: Option<&str>) -> Result<(), String> {
+    if note.is_some_and(|note| note.chars().count() > MAX_RELATION_NOTE_LENGTH) {
+        return Err(format!("Notes are limited to {} characters", MAX_RELATION_NOTE_LENGTH));
+    }
+    Ok(())
+}
+
+/// A relation from the document of the path to `target_document_id`
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateDocumentRelationRequest {
+    pub target_document_id: Uuid,
+    pub relation_type: DocumentRelationType,
+    pub note: Option<String>,
+}
+
+impl CreateDocumentRelationRequest {
+    pub fn validate(&self, source_document_id: Uuid) -> Result<(), String> {
+        if self.target_document_id == source_document_id {
+            return Err("A document cannot be related to itself".to_string());
+        }
+        validate_note(self.note.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateDocumentRelationRequest {
+    pub relation_type: Option<DocumentRelationType>,
+    /// An empty note removes it
+    pub note: Option<String>,
+}
+
+impl UpdateDocumentRelationRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_note(self.note.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct RelationSuggestionsQuery {
+    /// Default 10, at most 50
+    pub limit: Option<i64>,
+    /// Entities both documents must mention (default 2)
+    pub min_shared_entities: Option<i64>,
+}
+
+/// A document the knowledge graph suggests relating to, because their graphs name the
+/// same entities. Posting `target_document_id` and `relation_type` to the relations
+/// of `source_document_id` creates the relation.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentRelationSuggestion {
+    pub source_document_id: Uuid,
+    pub target_document_id: Uuid,
+    pub relation_type: DocumentRelationType,
+    /// The suggested document
+    pub document_id: Uuid,
+    pub filename: String,
+    /// Entities named in both graphs
+    pub shared_entities: Vec<String>,
+    /// Relationships of the suggested document's graph involving them
+    pub relationships: Vec<String>,
+}
+
+impl DocumentRelationSuggestion {
+    /// Suggest relating `document_id` to `other_document_id`. A typed relationship in the
+    /// other document's graph, like a receipt that `PAYS` an invoice, makes it the source.
+    pub fn new(
+        document_id: Uuid,
+        other_document_id: Uuid,
+        filename: String,
+        shared_entities: Vec<String>,
+        relationships: Vec<String>,
+    ) -> Self {
+        let (source_document_id, target_document_id, relation_type) = match suggest_relation_type(&relationships) {
+            Some(relation_type) => (other_document_id, document_id, relation_type),
+            None => (document_id, other_document_id, DocumentRelationType::Related),
+        };
+        Self {
+            source_document_id,
+            target_document_id,
+            relation_type,
+            document_id: other_document_id,
+            filename,
+            shared_entities,
+            relationships,
+        }
+    }
+}
+
+/// The relation type graph relationships like `PAYS` or `AMENDS` point to; a relationship
+/// of the suggested document's graph makes it the relation's source
+pub fn suggest_relation_type(relationships: &[String]) -> Option<DocumentRelationType> {
+    let relationships: Vec<String> = relationships.iter().map(|r| r.to_lowercase()).collect();
+    let mentions = |words: &[&str]| relationships.iter().any(|r| words.iter().any(|word| r.contains(word)));
+
+    if mentions(&["amend"]) {
+        Some(DocumentRelationType::Amends)
+    } else if mentions(&["pays", "paid", "payment", "settle"]) {
+        Some(DocumentRelationType::PaymentFor)
+    } else if mentions(&["supersede", "replace"]) {
+        Some(DocumentRelationType::Supersedes)
+    } else if mentions(&["respond", "repl"]) {
+        Some(DocumentRelationType::RespondsTo)
+    } else if mentions(&["attach", "enclos"]) {
+        Some(DocumentRelationType::AttachmentOf)
+    } else if mentions(&["refer", "cite"]) {
+        Some(DocumentRelationType::References)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relation_type_round_trip() {
+        for relation_type in [
+            DocumentRelationType::Related,
+            DocumentRelationType::PaymentFor,
+            DocumentRelationType::AttachmentOf,
+        ] {
+            assert_eq!(DocumentRelationType::try_from(relation_type.to_string()), Ok(relation_type));
+        }
+        assert!(DocumentRelationType::try_from("unrelated".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_suggest_relation_type() {
+        let suggest = |relationships: &[&str]| {
+            suggest_relation_type(&relationships.iter().map(|r| r.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(suggest(&["ISSUED_BY", "PAYS"]), Some(DocumentRelationType::PaymentFor));
+        assert_eq!(suggest(&["AMENDS", "REFERENCES"]), Some(DocumentRelationType::Amends));
+        assert_eq!(suggest(&["Replaces"]), Some(DocumentRelationType::Supersedes));
+        assert_eq!(suggest(&["WORKS_FOR"]), None);
+        assert_eq!(suggest(&[]), None);
+    }
+
+    #[test]
+    fn test_suggestion_direction() {
+        let (invoice, receipt) = (Uuid::new_v4(), Uuid::new_v4());
+        let typed = DocumentRelationSuggestion::new(invoice, receipt, "receipt.pdf".to_string(), vec![], vec!["PAYS".to_string()]);
+        assert_eq!((typed.source_document_id, typed.target_document_id), (receipt, invoice));
+        assert_eq!(typed.relation_type, DocumentRelationType::PaymentFor);
+
+        let untyped = DocumentRelationSuggestion::new(invoice, receipt, "receipt.pdf".to_string(), vec![], vec![]);
+        assert_eq!((untyped.source_document_id, untyped.target_document_id), (invoice, receipt));
+        assert_eq!(untyped.relation_type, DocumentRelationType::Related);
+    }
+}
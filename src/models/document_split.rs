@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// How document boundaries inside a batch scan are found
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum SplitMethod {
+    /// Blank pages separate documents and are dropped
+    #[serde(rename = "blank_page")]
+    BlankPage,
+    /// Separator sheets carrying a marker text separate documents and are dropped
+    #[serde(rename = "separator_sheet")]
+    SeparatorSheet,
+    /// The LLM reads the start of every page and decides where documents begin
+    #[serde(rename = "llm")]
+    Llm,
+}
+
+impl std::fmt::Display for SplitMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SplitMethod::BlankPage => write!(f, "blank_page"),
+            SplitMethod::SeparatorSheet => write!(f, "separator_sheet"),
+            SplitMethod::Llm => write!(f, "llm"),
+        }
+    }
+}
+
+impl TryFrom<String> for SplitMethod {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "blank_page" => Ok(SplitMethod::BlankPage),
+            "separator_sheet" => Ok(SplitMethod::SeparatorSheet),
+            "llm" => Ok(SplitMethod::Llm),
+            _ => Err(format!("Unknown split method: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SplitDocumentRequest {
+    pub method: SplitMethod,
+    /// Marker printed on separator sheets (case-insensitive), defaults to `SPLIT_SEPARATOR_TEXT`
+    pub separator_text: Option<String>,
+    /// Only report the detected page ranges without creating documents
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SplitPart {
+    pub start_page: i32,
+    pub end_page: i32,
+    /// Created child document, absent for dry runs
+    pub document_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SplitDocumentResponse {
+    pub parent_document_id: Uuid,
+    pub method: SplitMethod,
+    pub page_count: i32,
+    pub parts: Vec<SplitPart>,
+}
+
+/// Link between an uploaded batch scan and a document split out of it
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentSplit {
+    pub id: Uuid,
+    pub parent_document_id: Uuid,
+    pub child_document_id: Uuid,
+    pub start_page: i32,
+    pub end_page: i32,
+    #[sqlx(try_from = "String")]
+    pub method: SplitMethod,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// `auto` uses the LLM when one is configured and falls back to the heuristic
+pub const TITLE_MODES: [&str; 3] = ["auto", "llm", "heuristic"];
+
+/// A title generated for a document, and the name it had before
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentTitle {
+    pub document_id: Uuid,
+    /// The name before the first generated title, restored when the title is undone
+    pub original_filename: String,
+    pub title: String,
+    /// `llm` or `heuristic`
+    pub generated_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct GenerateTitleQuery {
+    /// `auto` (default), `llm` or `heuristic`
+    pub mode: Option<String>,
+    /// Only propose the title, without renaming the document
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GeneratedTitleResponse {
+    pub title: String,
+    /// The display name the title gives, with the file's extension
+    pub filename: String,
+    pub generated_by: String,
+    /// Whether the document was renamed
+    pub applied: bool,
+}
@@ -0,0 +1,290 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Types a custom field can have
+pub const CUSTOM_FIELD_TYPES: [&str; 5] = ["text", "date", "amount", "currency", "line_items"];
+
+/// Most custom fields a document type can have
+pub const MAX_CUSTOM_FIELDS: usize = 50;
+
+const MAX_FIELD_NAME_LENGTH: usize = 64;
+
+/// Longest value of a `text` field, in characters
+const MAX_TEXT_LENGTH: usize = 2000;
+
+/// Most entries of a `line_items` field
+const MAX_LINE_ITEMS: usize = 500;
+
+/// Keys of a `line_items` entry besides `description`, all amounts
+const LINE_ITEM_AMOUNTS: [&str; 3] = ["quantity", "unit_price", "amount"];
+
+/// A custom field that documents of a type carry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct CustomFieldDefinition {
+    /// Lowercase letters, digits and underscores, e.g. `due_date`
+    pub name: String,
+    /// `text`, `date` (YYYY-MM-DD), `amount` (a number), `currency` (an ISO 4217 code)
+    /// or `line_items` (a list of `description`, `quantity`, `unit_price` and `amount`)
+    #[serde(rename = "type")]
+    pub field_type: String,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// A kind of document such as Invoice, Contract or Receipt
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentType {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[schema(value_type = Vec<CustomFieldDefinition>)]
+    pub fields: sqlx::types::Json<Vec<CustomFieldDefinition>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateDocumentTypeRequest {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Vec<CustomFieldDefinition>,
+}
+
+/// `fields`, when given, replaces the type's fields as a whole
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateDocumentTypeRequest {
+    pub name: Option<String>,
+    pub fields: Option<Vec<CustomFieldDefinition>>,
+}
+
+/// A document's type and the values of its custom fields
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentCustomFields {
+    pub document_id: Uuid,
+    pub document_type_id: Uuid,
+    pub document_type_name: String,
+    /// Field name to value
+    #[schema(value_type = Object)]
+    pub fields: sqlx::types::Json<Map<String, Value>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Set a document's type and custom field values, replacing any it had
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetDocumentCustomFieldsRequest {
+    pub document_type_id: Uuid,
+    /// Field name to value; null or missing values are left empty
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub fields: Map<String, Value>,
+}
+
+impl DocumentType {
+    /// Checks values against the type's fields and returns them normalized: dates as
+    /// YYYY-MM-DD, amounts as numbers and currency codes in upper case. Null values
+    /// are dropped.
+    pub fn validate_values(&self, values: &Map<String, Value>) -> Result<Map<String, Value>, String> {
+        let mut normalized = Map::new();
+        for (name, value) in values {
+            let key = name.trim().to_lowercase();
+            let Some(field) = self.fields.iter().find(|field| field.name == key) else {
+                return Err(format!("'{}' is not a field of {}", name, self.name));
+            };
+            if value.is_null() {
+                continue;
+            }
+            let value = normalize_value(&field.field_type, value)
+                .ok_or_else(|| format!("{} is not a valid {} for field '{}'", value, field.field_type, field.name))?;
+            normalized.insert(field.name.clone(), value);
+        }
+
+        if let Some(missing) = self
+            .fields
+            .iter()
+            .find(|field| field.required && !normalized.contains_key(&field.name))
+        {
+            return Err(format!("Field '{}' is required", missing.name));
+        }
+        Ok(normalized)
+    }
+}
+
+/// Checks field definitions and returns them with their names trimmed and lowercased
+pub fn normalize_field_definitions(fields: &[CustomFieldDefinition]) -> Result<Vec<CustomFieldDefinition>, String> {
+    if fields.len() > MAX_CUSTOM_FIELDS {
+        return Err(format!("A document type can have at most {} fields", MAX_CUSTOM_FIELDS));
+    }
+
+    let mut normalized: Vec<CustomFieldDefinition> = Vec::with_capacity(fields.len());
+    for field in fields {
+        let name = field.name.trim().to_lowercase();
+        if !is_valid_field_name(&name) {
+            return Err(format!(
+                "'{}' is not a valid field name; use letters, digits and underscores",
+                field.name
+            ));
+        }
+        if !CUSTOM_FIELD_TYPES.contains(&field.field_type.as_str()) {
+            return Err(format!("Field type must be one of {}", CUSTOM_FIELD_TYPES.join(", ")));
+        }
+        if normalized.iter().any(|existing| existing.name == name) {
+            return Err(format!("Field '{}' is defined twice", name));
+        }
+        normalized.push(CustomFieldDefinition {
+            name,
+            field_type: field.field_type.clone(),
+            required: field.required,
+        });
+    }
+    Ok(normalized)
+}
+
+/// Whether a name can be used for a custom field, and so in `field.<name>:` searches
+pub fn is_valid_field_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_FIELD_NAME_LENGTH
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// The value in its stored form, or None when it is not valid for the type
+fn normalize_value(field_type: &str, value: &Value) -> Option<Value> {
+    match field_type {
+        "text" => {
+            let text = value.as_str()?.trim();
+            (!text.is_empty() && text.chars().count() <= MAX_TEXT_LENGTH).then(|| Value::String(text.to_string()))
+        }
+        "date" => {
+            let date = NaiveDate::parse_from_str(value.as_str()?.trim(), "%Y-%m-%d").ok()?;
+            Some(Value::String(date.format("%Y-%m-%d").to_string()))
+        }
+        "amount" => {
+            let amount = match value {
+                Value::Number(number) => number.as_f64()?,
+                Value::String(text) => text.trim().parse::<f64>().ok()?,
+                _ => return None,
+            };
+            serde_json::Number::from_f64(amount).map(Value::Number)
+        }
+        "currency" => {
+            let code = value.as_str()?.trim();
+            (code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()))
+                .then(|| Value::String(code.to_ascii_uppercase()))
+        }
+        "line_items" => {
+            let items = value.as_array()?;
+            if items.len() > MAX_LINE_ITEMS {
+                return None;
+            }
+            items.iter().map(normalize_line_item).collect::<Option<Vec<_>>>().map(Value::Array)
+        }
+        _ => None,
+    }
+}
+
+/// A `line_items` entry with a description and optional amounts
+fn normalize_line_item(item: &Value) -> Option<Value> {
+    let item = item.as_object()?;
+    let mut normalized = Map::new();
+    for (key, value) in item {
+        if value.is_null() {
+            continue;
+        }
+        let value = if key == "description" {
+            normalize_value("text", value)?
+        } else if LINE_ITEM_AMOUNTS.contains(&key.as_str()) {
+            normalize_value("amount", value)?
+        } else {
+            return None;
+        };
+        normalized.insert(key.clone(), value);
+    }
+    normalized.contains_key("description").then_some(Value::Object(normalized))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn invoice() -> DocumentType {
+        let field = |name: &str, field_type: &str, required: bool| CustomFieldDefinition {
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+            required,
+        };
+        DocumentType {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            name: "Invoice".to_string(),
+            fields: sqlx::types::Json(vec![
+                field("vendor", "text", false),
+                field("due_date", "date", false),
+                field("total", "amount", true),
+                field("currency", "currency", false),
+            ]),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn values(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_validate_values_normalizes() {
+        let normalized = invoice()
+            .validate_values(&values(json!({
+                "Vendor": " ACME Corp ",
+                "due_date": "2024-06-30",
+                "total": "120.50",
+                "currency": "eur"
+            })))
+            .unwrap();
+
+        assert_eq!(
+            Value::Object(normalized),
+            json!({"vendor": "ACME Corp", "due_date": "2024-06-30", "total": 120.5, "currency": "EUR"})
+        );
+    }
+
+    #[test]
+    fn test_validate_values_rejects_invalid() {
+        let invoice = invoice();
+        assert!(invoice.validate_values(&values(json!({"vendor": "ACME"}))).is_err());
+        assert!(invoice.validate_values(&values(json!({"total": 1, "due_date": "30.06.2024"}))).is_err());
+        assert!(invoice.validate_values(&values(json!({"total": 1, "currency": "euro"}))).is_err());
+        assert!(invoice.validate_values(&values(json!({"total": 1, "iban": "DE00"}))).is_err());
+        assert!(invoice.validate_values(&values(json!({"total": 1, "vendor": null}))).is_ok());
+    }
+
+    #[test]
+    fn test_line_items() {
+        let items = json!([{"description": " Consulting ", "quantity": "2", "unit_price": 50, "amount": 100}]);
+        assert_eq!(
+            normalize_value("line_items", &items),
+            Some(json!([{"description": "Consulting", "quantity": 2.0, "unit_price": 50.0, "amount": 100.0}]))
+        );
+        assert_eq!(normalize_value("line_items", &json!([{"amount": 100}])), None);
+        assert_eq!(normalize_value("line_items", &json!([{"description": "Consulting", "sku": "C-1"}])), None);
+    }
+
+    #[test]
+    fn test_normalize_field_definitions() {
+        let field = |name: &str, field_type: &str| CustomFieldDefinition {
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+            required: false,
+        };
+
+        let normalized = normalize_field_definitions(&[field(" Due_Date ", "date")]).unwrap();
+        assert_eq!(normalized[0].name, "due_date");
+        assert!(normalize_field_definitions(&[field("due date", "date")]).is_err());
+        assert!(normalize_field_definitions(&[field("total", "money")]).is_err());
+        assert!(normalize_field_definitions(&[field("total", "amount"), field("TOTAL", "amount")]).is_err());
+    }
+}
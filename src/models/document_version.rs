@@ -0,0 +1,142 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Earlier contents of a document
+#[derive(Debug, Clone, FromRow)]
+pub struct DocumentVersion {
+    /// Also the storage id the version's file is saved under
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub version_number: i32,
+    pub filename: String,
+    pub file_path: String,
+    pub file_size: i64,
+    pub file_hash: Option<String>,
+    pub mime_type: String,
+    pub ocr_text: Option<String>,
+    pub ocr_confidence: Option<f32>,
+    pub ocr_word_count: Option<i32>,
+    pub original_modified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub superseded_at: DateTime<Utc>,
+    /// Unredacted contents only the document's owner and admins may see
+    pub restricted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentVersionResponse {
+    pub version_number: i32,
+    pub filename: String,
+    pub file_size: i64,
+    pub file_hash: Option<String>,
+    pub mime_type: String,
+    pub ocr_confidence: Option<f32>,
+    pub ocr_word_count: Option<i32>,
+    /// Modification time reported by the source the file was synced from
+    pub original_modified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    /// When newer contents replaced this version; None for the current version
+    pub superseded_at: Option<DateTime<Utc>>,
+    pub current: bool,
+    /// Contents from before a redaction, hidden from everyone but the owner and admins
+    pub restricted: bool,
+}
+
+impl From<DocumentVersion> for DocumentVersionResponse {
+    fn from(version: DocumentVersion) -> Self {
+        Self {
+            version_number: version.version_number,
+            filename: version.filename,
+            file_size: version.file_size,
+            file_hash: version.file_hash,
+            mime_type: version.mime_type,
+            ocr_confidence: version.ocr_confidence,
+            ocr_word_count: version.ocr_word_count,
+            original_modified_at: version.original_modified_at,
+            created_at: version.created_at,
+            superseded_at: Some(version.superseded_at),
+            current: false,
+            restricted: version.restricted,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentVersionsResponse {
+    pub document_id: Uuid,
+    pub current_version: i32,
+    /// Newest first, starting with the current version
+    pub versions: Vec<DocumentVersionResponse>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct DocumentVersionDiffQuery {
+    /// Older version to compare
+    pub from: i32,
+    /// Newer version to compare (default: the current version)
+    pub to: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+}
+
+/// Line diff between the OCR texts of two versions
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentVersionDiffResponse {
+    pub document_id: Uuid,
+    pub from_version: i32,
+    pub to_version: i32,
+    pub lines: Vec<DiffLine>,
+    pub lines_added: i64,
+    pub lines_removed: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct DocumentCompareQuery {
+    /// Document compared from, e.g. the earlier revision
+    pub a: Uuid,
+    /// Document compared to
+    pub b: Uuid,
+}
+
+/// How different one page of two documents looks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PageDifference {
+    /// 1-based page number
+    pub page: u32,
+    /// 0.0 for pages that look the same to 1.0 for pages with nothing in common;
+    /// 1.0 for a page only one document has
+    pub score: f64,
+    pub in_a: bool,
+    pub in_b: bool,
+}
+
+/// Line diff between the OCR texts of two documents and how their pages differ
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentCompareResponse {
+    pub a: Uuid,
+    pub b: Uuid,
+    pub lines: Vec<DiffLine>,
+    pub lines_added: i64,
+    pub lines_removed: i64,
+    /// Share of lines the texts have in common, from 0.0 to 1.0
+    pub text_similarity: f64,
+    /// Whether the pages were rendered and compared; false for documents that cannot
+    /// be rendered, which have no page scores
+    pub pages_compared: bool,
+    pub pages: Vec<PageDifference>,
+}
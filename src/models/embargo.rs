@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use super::MAX_BULK_DOCUMENTS;
+
+/// Longest embargo a source can put on the documents it ingests, in days
+pub const MAX_EMBARGO_DAYS: i32 = 36_500;
+
+/// A document hidden from everyone but admins and its owner until a date or until an
+/// admin releases it: shares, workspaces, share links and guest portals do not reach
+/// it. For pre-publication archives, HR files and the like.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentEmbargo {
+    pub document_id: Uuid,
+    pub original_filename: String,
+    /// When the document becomes visible; None keeps it hidden until it is released
+    pub embargoed_until: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    /// The source whose embargo the document got at ingestion, if any
+    pub source_id: Option<Uuid>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub released_at: Option<DateTime<Utc>>,
+    pub released_by: Option<Uuid>,
+}
+
+impl DocumentEmbargo {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.released_at.is_none() && self.embargoed_until.is_none_or(|until| until > now)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmbargoDocumentsRequest {
+    pub document_ids: Vec<Uuid>,
+    /// When the documents become visible; without it they stay hidden until released
+    pub until: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+impl EmbargoDocumentsRequest {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), String> {
+        if self.document_ids.is_empty() {
+            return Err("No documents given".to_string());
+        }
+        if self.document_ids.len() > MAX_BULK_DOCUMENTS {
+            return Err(format!("At most {} documents can be embargoed at once", MAX_BULK_DOCUMENTS));
+        }
+        if self.until.is_some_and(|until| until <= now) {
+            return Err("The embargo date must be in the future".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmbargoDocumentsResponse {
+    /// Documents embargoed, including those whose earlier embargo was replaced
+    pub embargoed: Vec<Uuid>,
+    /// Documents that do not exist
+    pub not_found: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReleaseEmbargoesRequest {
+    pub document_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReleaseEmbargoesResponse {
+    /// Documents that are visible again
+    pub released: Vec<Uuid>,
+    /// Documents that were not under an active embargo
+    pub not_embargoed: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct ListEmbargoesQuery {
+    /// Also list released and expired embargoes (default: false)
+    #[serde(default)]
+    pub include_inactive: bool,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// The embargo a source puts on every document it ingests
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SourceEmbargo {
+    pub source_id: Uuid,
+    /// Days the documents stay hidden after ingestion; None keeps them hidden until released
+    pub embargo_days: Option<i32>,
+    pub reason: Option<String>,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSourceEmbargo {
+    pub embargo_days: Option<i32>,
+    pub reason: Option<String>,
+}
+
+impl UpdateSourceEmbargo {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.embargo_days {
+            Some(days) if !(1..=MAX_EMBARGO_DAYS).contains(&days) => {
+                Err(format!("The embargo must last between 1 and {} days", MAX_EMBARGO_DAYS))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_embargo_is_active() {
+        let now = Utc::now();
+        let embargo = DocumentEmbargo {
+            document_id: Uuid::new_v4(),
+            original_filename: "salaries.pdf".to_string(),
+            embargoed_until: None,
+            reason: None,
+            source_id: None,
+            created_by: None,
+            created_at: now,
+            released_at: None,
+            released_by: None,
+        };
+        assert!(embargo.is_active(now));
+        assert!(DocumentEmbargo { embargoed_until: Some(now + Duration::days(1)), ..embargo.clone() }.is_active(now));
+        assert!(!DocumentEmbargo { embargoed_until: Some(now - Duration::days(1)), ..embargo.clone() }.is_active(now));
+        assert!(!DocumentEmbargo { released_at: Some(now), ..embargo }.is_active(now));
+    }
+
+    #[test]
+    fn test_embargo_request_validation() {
+        let now = Utc::now();
+        let request = EmbargoDocumentsRequest { document_ids: vec![Uuid::new_v4()], until: None, reason: None };
+        assert!(request.validate(now).is_ok());
+        assert!(EmbargoDocumentsRequest { until: Some(now + Duration::hours(1)), ..request.clone() }.validate(now).is_ok());
+        assert!(EmbargoDocumentsRequest { until: Some(now - Duration::hours(1)), ..request.clone() }.validate(now).is_err());
+        assert!(EmbargoDocumentsRequest { document_ids: vec![], ..request }.validate(now).is_err());
+
+        assert!(UpdateSourceEmbargo { embargo_days: None, reason: None }.validate().is_ok());
+        assert!(UpdateSourceEmbargo { embargo_days: Some(30), reason: None }.validate().is_ok());
+        assert!(UpdateSourceEmbargo { embargo_days: Some(0), reason: None }.validate().is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Whose data a key seals: a workspace's documents and sources, or the personal data
+/// of a user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyOwner {
+    User(Uuid),
+    Workspace(Uuid),
+}
+
+impl KeyOwner {
+    /// The key of the workspace for rows in one, otherwise the key of their owner
+    pub fn of(user_id: Uuid, workspace_id: Option<Uuid>) -> Self {
+        workspace_id.map_or(KeyOwner::User(user_id), KeyOwner::Workspace)
+    }
+
+    /// `(user_id, workspace_id)` as stored with keys and their events
+    pub fn ids(self) -> (Option<Uuid>, Option<Uuid>) {
+        match self {
+            KeyOwner::User(user_id) => (Some(user_id), None),
+            KeyOwner::Workspace(workspace_id) => (None, Some(workspace_id)),
+        }
+    }
+}
+
+impl std::fmt::Display for KeyOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyOwner::User(user_id) => write!(f, "user {}", user_id),
+            KeyOwner::Workspace(workspace_id) => write!(f, "workspace {}", workspace_id),
+        }
+    }
+}
+
+/// A version of a user's or a workspace's data encryption key. The key material itself
+/// is only stored wrapped and never leaves the server.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct EncryptionKey {
+    pub id: Uuid,
+    /// Set for the keys of users, None for the keys of workspaces
+    pub user_id: Option<Uuid>,
+    pub workspace_id: Option<Uuid>,
+    pub version: i32,
+    /// `active` encrypts new data; `retired` versions only decrypt
+    pub status: String,
+    /// `local` for the server master key, `vault` for a customer-managed Vault transit key
+    pub provider: String,
+    /// Customer-managed key wrapping this version, e.g. `vault:tenant-a`
+    pub kms_key_ref: Option<String>,
+    #[serde(skip)]
+    pub wrapped_key: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+impl EncryptionKey {
+    pub fn owner(&self) -> KeyOwner {
+        match (self.user_id, self.workspace_id) {
+            (_, Some(workspace_id)) => KeyOwner::Workspace(workspace_id),
+            (Some(user_id), None) => KeyOwner::User(user_id),
+            (None, None) => unreachable!("encryption_keys_one_owner requires a user or a workspace"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct EncryptionKeyEvent {
+    pub id: Uuid,
+    pub key_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub workspace_id: Option<Uuid>,
+    pub action: String,
+    pub actor_user_id: Option<Uuid>,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct RotateEncryptionKeyRequest {
+    /// Customer-managed key that wraps the new version and, from now on, all older
+    /// versions; the server master key when omitted
+    pub kms_key_ref: Option<String>,
+    /// Re-encrypt the stored documents sealed with this key with the new version in
+    /// the background
+    #[serde(default)]
+    pub reencrypt_files: bool,
+}
@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Most documents an entity profile is built from, most recent first
+pub const MAX_ENTITY_PROFILE_DOCUMENTS: i64 = 500;
+
+/// How entity names are compared: ignoring case and surrounding spaces
+pub fn normalize_entity_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// An entity of the knowledge graph: everything of one type and name found in a
+/// user's documents
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct GraphEntity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub label: String,
+    pub name: String,
+    #[serde(skip)]
+    pub normalized_name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl GraphEntity {
+    pub fn matches(&self, label: &str, name: &str) -> bool {
+        self.label == label && self.normalized_name == normalize_entity_name(name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct GraphEntitySummary {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub entity: GraphEntity,
+    /// Documents whose latest graph names the entity as extracted; corrections are
+    /// only taken into account on the entity's page
+    pub document_count: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct EntitiesQuery {
+    /// Part of the entity name
+    pub q: Option<String>,
+    /// Only entities of this type, e.g. `Person`
+    pub label: Option<String>,
+    /// Default 50, at most 200
+    pub limit: Option<i64>,
+}
+
+/// A document mentioning the entity
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct EntityMention {
+    pub document_id: Uuid,
+    pub filename: String,
+    /// When the document was created at its source, or else uploaded
+    pub document_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EntityAttributeValue {
+    pub value: Value,
+    /// Documents the value was extracted from
+    pub document_ids: Vec<Uuid>,
+}
+
+/// An attribute of the entity with every value found for it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EntityAttribute {
+    pub key: String,
+    pub values: Vec<EntityAttributeValue>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationshipDirection {
+    /// The entity is the source: "entity RELATIONSHIP other"
+    Outgoing,
+    /// The entity is the target: "other RELATIONSHIP entity"
+    Incoming,
+}
+
+/// A relationship to another entity, merged across documents
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EntityRelationship {
+    pub direction: RelationshipDirection,
+    pub relationship: String,
+    pub label: String,
+    pub name: String,
+    /// The other entity's page
+    pub entity_id: Option<Uuid>,
+    pub document_ids: Vec<Uuid>,
+}
+
+/// Mentions in one month, by document date
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EntityTimelineBucket {
+    /// `YYYY-MM`
+    pub period: String,
+    pub mentions: i64,
+}
+
+/// Everything the documents say about an entity
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EntityProfile {
+    pub entity: GraphEntity,
+    /// Documents mentioning the entity, oldest first
+    pub documents: Vec<EntityMention>,
+    pub relationships: Vec<EntityRelationship>,
+    pub attributes: Vec<EntityAttribute>,
+    pub timeline: Vec<EntityTimelineBucket>,
+    /// More documents mention the entity than the profile was built from
+    pub truncated: bool,
+}
@@ -0,0 +1,206 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Every event type that can be delivered to webhooks and event consumers.
+///
+/// The wire name (`document.created`, ...) is stable; payload changes are
+/// signalled by bumping the per-type schema version instead of renaming.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+pub enum EventType {
+    #[serde(rename = "document.created")]
+    DocumentCreated,
+    #[serde(rename = "document.deleted")]
+    DocumentDeleted,
+    #[serde(rename = "document.updated")]
+    DocumentUpdated,
+    #[serde(rename = "document.labels_changed")]
+    DocumentLabelsChanged,
+    #[serde(rename = "ocr.completed")]
+    OcrCompleted,
+    #[serde(rename = "ocr.failed")]
+    OcrFailed,
+    #[serde(rename = "analysis.completed")]
+    AnalysisCompleted,
+    #[serde(rename = "sync.completed")]
+    SyncCompleted,
+    #[serde(rename = "sync.failed")]
+    SyncFailed,
+    /// OCR queue counts of a user. Only streamed to connected clients, never stored,
+    /// replayed or delivered to webhooks.
+    #[serde(rename = "queue.progress")]
+    QueueProgress,
+    /// How far a document is through ingestion, OCR and analysis. Only streamed,
+    /// like `queue.progress`.
+    #[serde(rename = "document.progress")]
+    DocumentProgress,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 11] = [
+        EventType::DocumentCreated,
+        EventType::DocumentDeleted,
+        EventType::DocumentUpdated,
+        EventType::DocumentLabelsChanged,
+        EventType::OcrCompleted,
+        EventType::OcrFailed,
+        EventType::AnalysisCompleted,
+        EventType::SyncCompleted,
+        EventType::SyncFailed,
+        EventType::QueueProgress,
+        EventType::DocumentProgress,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::DocumentCreated => "document.created",
+            EventType::DocumentDeleted => "document.deleted",
+            EventType::DocumentUpdated => "document.updated",
+            EventType::DocumentLabelsChanged => "document.labels_changed",
+            EventType::OcrCompleted => "ocr.completed",
+            EventType::OcrFailed => "ocr.failed",
+            EventType::AnalysisCompleted => "analysis.completed",
+            EventType::SyncCompleted => "sync.completed",
+            EventType::SyncFailed => "sync.failed",
+            EventType::QueueProgress => "queue.progress",
+            EventType::DocumentProgress => "document.progress",
+        }
+    }
+
+    /// Whether events of this type are kept in the event log
+    pub fn is_stored(&self) -> bool {
+        !matches!(self, EventType::QueueProgress | EventType::DocumentProgress)
+    }
+
+    /// Current payload schema version for this event type
+    pub fn schema_version(&self) -> i32 {
+        match self {
+            EventType::DocumentCreated
+            | EventType::DocumentDeleted
+            | EventType::DocumentUpdated
+            | EventType::DocumentLabelsChanged
+            | EventType::OcrCompleted
+            | EventType::OcrFailed
+            | EventType::AnalysisCompleted
+            | EventType::SyncCompleted
+            | EventType::SyncFailed
+            | EventType::QueueProgress
+            | EventType::DocumentProgress => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<String> for EventType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        EventType::ALL
+            .iter()
+            .copied()
+            .find(|event_type| event_type.as_str() == value)
+            .ok_or_else(|| format!("Invalid event type: {}", value))
+    }
+}
+
+/// Envelope wrapped around every event delivery
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EventEnvelope {
+    pub event_id: Uuid,
+    pub event_type: EventType,
+    pub schema_version: i32,
+    pub occurred_at: DateTime<Utc>,
+    pub user_id: Option<Uuid>,
+    pub resource_id: Option<Uuid>,
+    pub data: serde_json::Value,
+}
+
+/// Event as persisted in the `events` table
+#[derive(Debug, Clone, FromRow)]
+pub struct StoredEvent {
+    pub id: Uuid,
+    #[sqlx(try_from = "String")]
+    pub event_type: EventType,
+    pub schema_version: i32,
+    pub user_id: Option<Uuid>,
+    pub resource_id: Option<Uuid>,
+    pub payload: serde_json::Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<StoredEvent> for EventEnvelope {
+    fn from(event: StoredEvent) -> Self {
+        Self {
+            event_id: event.id,
+            event_type: event.event_type,
+            schema_version: event.schema_version,
+            occurred_at: event.occurred_at,
+            user_id: event.user_id,
+            resource_id: event.resource_id,
+            data: event.payload,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EventSchemaResponse {
+    pub event_type: EventType,
+    pub schema_version: i32,
+    /// JSON Schema describing the full envelope including `data`
+    pub schema: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReplayEventsRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// URL that should receive the re-delivered events
+    pub target_url: String,
+    /// Restrict the replay to these event types (all types when omitted)
+    pub event_types: Option<Vec<EventType>>,
+    /// Continue a replay after this event, the `next_after_event_id` of the previous batch
+    pub after_event_id: Option<Uuid>,
+}
+
+/// One batch of a replay. Events are sent in batches of at most 100, for at most a minute.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReplayEventsResponse {
+    /// Events sent in this batch
+    pub total: usize,
+    pub delivered: usize,
+    pub failed: usize,
+    pub failed_event_ids: Vec<Uuid>,
+    /// Set while events of the range remain; replay again with it as `after_event_id`
+    pub next_after_event_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EventStreamQuery {
+    /// Resume after this event: stored events the client missed are sent first
+    pub last_event_id: Option<Uuid>,
+    /// Comma-separated event types to receive, e.g. `document.created,ocr.completed`;
+    /// every type when omitted
+    pub event_types: Option<String>,
+}
+
+impl EventStreamQuery {
+    pub fn parse_event_types(&self) -> Result<Option<Vec<EventType>>, String> {
+        let Some(event_types) = self.event_types.as_deref() else {
+            return Ok(None);
+        };
+        event_types
+            .split(',')
+            .map(str::trim)
+            .filter(|event_type| !event_type.is_empty())
+            .map(|event_type| EventType::try_from(event_type.to_string()))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+    }
+}
@@ -0,0 +1,268 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::DocumentCustomFields;
+
+/// Version of the manifest layout, raised when it changes incompatibly
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+/// Name of the manifest inside an export archive
+pub const EXPORT_MANIFEST_NAME: &str = "manifest.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    Queued,
+    /// Being written, or interrupted and started over at the next start
+    Running,
+    /// The archive can be downloaded; files that could not be read are missing from it
+    Completed,
+    Failed,
+}
+
+impl std::fmt::Display for ExportStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportStatus::Queued => write!(f, "queued"),
+            ExportStatus::Running => write!(f, "running"),
+            ExportStatus::Completed => write!(f, "completed"),
+            ExportStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl TryFrom<String> for ExportStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "queued" => Ok(ExportStatus::Queued),
+            "running" => Ok(ExportStatus::Running),
+            "completed" => Ok(ExportStatus::Completed),
+            "failed" => Ok(ExportStatus::Failed),
+            _ => Err(format!("Unknown export status: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct CreateExportRequest {
+    /// Export every user's library instead of your own; admins only
+    #[serde(default)]
+    pub all_users: bool,
+}
+
+/// A document that could not be exported or imported
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExportError {
+    pub document_id: Uuid,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Export {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub all_users: bool,
+    #[sqlx(try_from = "String")]
+    pub status: ExportStatus,
+    pub total_documents: i32,
+    pub processed_documents: i32,
+    /// Documents exported without their file
+    pub failed_documents: i32,
+    /// The first failures; `failed_documents` counts them all
+    #[schema(value_type = Vec<ExportError>)]
+    pub errors: sqlx::types::Json<Vec<ExportError>>,
+    /// Size of the finished archive in bytes
+    pub archive_size: Option<i64>,
+    /// Why the export failed as a whole
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Queued,
+    /// Being restored, or interrupted and started over at the next start
+    Running,
+    /// Every document was processed; some may have failed
+    Completed,
+    /// Stopped by an error that affects the whole archive, such as a missing manifest
+    Failed,
+}
+
+impl std::fmt::Display for ImportStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportStatus::Queued => write!(f, "queued"),
+            ImportStatus::Running => write!(f, "running"),
+            ImportStatus::Completed => write!(f, "completed"),
+            ImportStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl TryFrom<String> for ImportStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "queued" => Ok(ImportStatus::Queued),
+            "running" => Ok(ImportStatus::Running),
+            "completed" => Ok(ImportStatus::Completed),
+            "failed" => Ok(ImportStatus::Failed),
+            _ => Err(format!("Unknown import status: {}", value)),
+        }
+    }
+}
+
+/// A restore of an export archive
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Import {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Create the archive's users that do not exist; otherwise their documents are yours
+    pub recreate_users: bool,
+    /// Create the archive's labels that do not exist; otherwise only existing labels
+    /// of the same name are assigned
+    pub recreate_labels: bool,
+    #[sqlx(try_from = "String")]
+    pub status: ImportStatus,
+    pub total_documents: i32,
+    pub processed_documents: i32,
+    pub imported_documents: i32,
+    /// Documents whose owner already had the same file
+    pub skipped_documents: i32,
+    pub failed_documents: i32,
+    pub created_users: i32,
+    pub created_labels: i32,
+    /// The first failures, by document ID in the manifest; `failed_documents` counts them all
+    #[schema(value_type = Vec<ExportError>)]
+    pub errors: sqlx::types::Json<Vec<ExportError>>,
+    /// Why the import failed as a whole
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// `manifest.json` of an export archive. Every document's original file is stored
+/// next to it under `files/`, at the document's `file` path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub format_version: u32,
+    pub readur_version: String,
+    pub export_id: Uuid,
+    pub exported_by: Uuid,
+    pub exported_at: DateTime<Utc>,
+    /// Owners of the exported documents
+    pub users: Vec<ExportedUser>,
+    /// The owners' labels and the system labels, parents before their children
+    pub labels: Vec<ExportedLabel>,
+    pub documents: Vec<ExportedDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExportedUser {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExportedLabel {
+    pub id: Uuid,
+    /// None for system labels
+    pub user_id: Option<Uuid>,
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    pub description: Option<String>,
+    pub color: String,
+    pub background_color: Option<String>,
+    pub icon: Option<String>,
+    pub is_system: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedDocument {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub filename: String,
+    pub original_filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    /// SHA-256 of the archived file
+    pub file_hash: Option<String>,
+    /// Path of the original file in the archive; None when it could not be read
+    pub file: Option<String>,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub original_created_at: Option<DateTime<Utc>>,
+    pub original_modified_at: Option<DateTime<Utc>>,
+    pub source_path: Option<String>,
+    pub source_type: Option<String>,
+    pub source_metadata: Option<Value>,
+    /// Text extracted without OCR, such as from text files
+    pub content: Option<String>,
+    pub ocr_text: Option<String>,
+    pub ocr_confidence: Option<f32>,
+    pub ocr_word_count: Option<i32>,
+    pub ocr_status: Option<String>,
+    pub ocr_completed_at: Option<DateTime<Utc>>,
+    pub label_ids: Vec<Uuid>,
+    pub custom_fields: Option<DocumentCustomFields>,
+    pub graph: ExportedGraph,
+}
+
+/// Entities and relationships extracted from a document
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportedGraph {
+    pub nodes: Vec<ExportedGraphNode>,
+    pub edges: Vec<ExportedGraphEdge>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExportedGraphNode {
+    pub id: Uuid,
+    pub label: String,
+    pub name: String,
+    pub properties: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExportedGraphEdge {
+    pub id: Uuid,
+    pub source_node_id: Uuid,
+    pub target_node_id: Uuid,
+    pub relationship: String,
+    pub properties: Option<Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_round_trips() {
+        for status in [ExportStatus::Queued, ExportStatus::Running, ExportStatus::Completed, ExportStatus::Failed] {
+            assert_eq!(ExportStatus::try_from(status.to_string()), Ok(status));
+        }
+        assert!(ExportStatus::try_from("cancelled".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_create_request_defaults_to_own_library() {
+        let request: CreateExportRequest = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(!request.all_users);
+    }
+}
@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// What kind of problem a failure reason is, for grouping failures in the UI
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// The content or file name is already in the library
+    Duplicate,
+    UnsupportedFormat,
+    /// Corrupted, unparseable or oversized files
+    DamagedFile,
+    LowOcrConfidence,
+    /// OCR ran out of time or memory
+    ResourceLimit,
+    /// The file or its record could not be saved
+    Storage,
+    /// The source could not be read
+    Access,
+    /// Refused by the malware scanner or a pre-consume hook
+    Security,
+    #[default]
+    Other,
+}
+
+impl FailureCategory {
+    /// The category of a `failed_documents.failure_reason` or `documents.ocr_failure_reason`
+    pub fn from_reason(reason: &str) -> Option<Self> {
+        let category = match reason {
+            "duplicate_content" | "duplicate_filename" => Self::Duplicate,
+            "unsupported_format" => Self::UnsupportedFormat,
+            "file_corrupted" | "pdf_parsing_error" | "invalid_structure" | "file_too_large" | "pdf_font_encoding"
+            | "pdf_corruption" | "pdf_parsing_panic" => Self::DamagedFile,
+            "low_ocr_confidence" => Self::LowOcrConfidence,
+            "ocr_timeout" | "ocr_memory_limit" | "processing_timeout" | "memory_limit" => Self::ResourceLimit,
+            "storage_quota_exceeded" | "storage_error" | "database_error" => Self::Storage,
+            "access_denied" | "permission_denied" | "network_error" => Self::Access,
+            "virus_detected" | "policy_violation" => Self::Security,
+            "other" => Self::Other,
+            _ => return None,
+        };
+        Some(category)
+    }
+
+    /// The reasons in this category, for filtering by it
+    pub fn reasons(&self) -> &'static [&'static str] {
+        match self {
+            Self::Duplicate => &["duplicate_content", "duplicate_filename"],
+            Self::UnsupportedFormat => &["unsupported_format"],
+            Self::DamagedFile => &["file_corrupted", "pdf_parsing_error", "invalid_structure", "file_too_large"],
+            Self::LowOcrConfidence => &["low_ocr_confidence"],
+            Self::ResourceLimit => &["ocr_timeout", "ocr_memory_limit"],
+            Self::Storage => &["storage_quota_exceeded", "storage_error", "database_error"],
+            Self::Access => &["access_denied", "permission_denied", "network_error"],
+            Self::Security => &["virus_detected", "policy_violation"],
+            Self::Other => &["other"],
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Duplicate => "Duplicate",
+            Self::UnsupportedFormat => "Unsupported Format",
+            Self::DamagedFile => "Damaged File",
+            Self::LowOcrConfidence => "Low OCR Confidence",
+            Self::ResourceLimit => "Resource Limit",
+            Self::Storage => "Storage",
+            Self::Access => "Source Access",
+            Self::Security => "Security",
+            Self::Other => "Other",
+        }
+    }
+}
+
+impl std::str::FromStr for FailureCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "duplicate" => Ok(Self::Duplicate),
+            "unsupported_format" => Ok(Self::UnsupportedFormat),
+            "damaged_file" => Ok(Self::DamagedFile),
+            "low_ocr_confidence" => Ok(Self::LowOcrConfidence),
+            "resource_limit" => Ok(Self::ResourceLimit),
+            "storage" => Ok(Self::Storage),
+            "access" => Ok(Self::Access),
+            "security" => Ok(Self::Security),
+            "other" => Ok(Self::Other),
+            _ => Err(format!("Invalid failure category: {}", s)),
+        }
+    }
+}
+
+/// One recorded failure of a file, as listed in its failure history
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FailedDocumentAttempt {
+    /// The failed document record
+    pub id: Uuid,
+    pub failure_reason: String,
+    pub failure_stage: String,
+    #[sqlx(skip)]
+    pub category: FailureCategory,
+    pub error_message: Option<String>,
+    pub ingestion_source: String,
+    pub existing_document_id: Option<Uuid>,
+    pub retry_count: Option<i32>,
+    pub last_retry_at: Option<DateTime<Utc>>,
+    /// When the failure was requeued; None while it is still open
+    pub requeued_at: Option<DateTime<Utc>>,
+    pub requeued_document_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FailedDocumentHistory {
+    pub failed_document_id: Uuid,
+    pub filename: String,
+    /// Whether the failure can be requeued
+    pub retryable: bool,
+    /// Every failure of the same file, oldest first
+    pub attempts: Vec<FailedDocumentAttempt>,
+}
+
+/// How a failed document was requeued
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RequeueAction {
+    /// The document exists and was queued for OCR again
+    Ocr,
+    /// The kept file went through ingestion again
+    Ingestion,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FailedDocumentRequeue {
+    pub failed_document_id: Uuid,
+    pub action: RequeueAction,
+    /// The document now processing the file, when there is one
+    pub document_id: Option<Uuid>,
+    pub message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_reason_of_a_category_maps_back_to_it() {
+        for category in [
+            FailureCategory::Duplicate,
+            FailureCategory::UnsupportedFormat,
+            FailureCategory::DamagedFile,
+            FailureCategory::LowOcrConfidence,
+            FailureCategory::ResourceLimit,
+            FailureCategory::Storage,
+            FailureCategory::Access,
+            FailureCategory::Security,
+            FailureCategory::Other,
+        ] {
+            for reason in category.reasons() {
+                assert_eq!(FailureCategory::from_reason(reason), Some(category), "{}", reason);
+            }
+        }
+        assert_eq!(FailureCategory::from_reason("pdf_font_encoding"), Some(FailureCategory::DamagedFile));
+        assert_eq!(FailureCategory::from_reason("unknown"), None);
+    }
+}
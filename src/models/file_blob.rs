@@ -0,0 +1,18 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A document file stored once for every document with the same content
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FileBlob {
+    /// SHA-256 of the content
+    pub hash: String,
+    pub storage_path: String,
+    pub size: i64,
+    /// Documents pointing at the blob
+    pub ref_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
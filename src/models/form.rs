@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// A field a form layout is known to contain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct FormTemplateField {
+    pub key: String,
+    pub label: String,
+}
+
+/// A recurring form layout learned from previously extracted documents
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FormTemplate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[schema(value_type = Vec<FormTemplateField>)]
+    pub fields: sqlx::types::Json<Vec<FormTemplateField>>,
+    pub times_matched: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Key/value metadata extracted from a form document
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentFormFields {
+    pub document_id: Uuid,
+    pub template_id: Option<Uuid>,
+    /// Normalized field key to value; `null` when a template field was not found
+    #[schema(value_type = Object)]
+    pub fields: sqlx::types::Json<serde_json::Map<String, serde_json::Value>>,
+    pub extracted_at: DateTime<Utc>,
+}
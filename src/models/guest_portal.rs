@@ -0,0 +1,218 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+const MAX_SLUG_LENGTH: usize = 63;
+const MAX_TITLE_LENGTH: usize = 200;
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+pub const DEFAULT_GUEST_REQUESTS_PER_MINUTE: i32 = 30;
+const MAX_GUEST_REQUESTS_PER_MINUTE: i32 = 10_000;
+
+/// A label or collection an admin exposes read-only to visitors without an account, e.g.
+/// a public records archive. Visitors only see the documents carrying the label, or
+/// those of the collection and its subcollections.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct GuestPortal {
+    pub id: Uuid,
+    /// Part of the public URL, `/api/public/portals/{slug}`
+    pub slug: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// Exactly one of the label and the collection is set
+    pub label_id: Option<Uuid>,
+    pub collection_id: Option<Uuid>,
+    /// Whether visitors may download files, or only find and read about them
+    pub allow_download: bool,
+    /// Whether visitors see and search the OCR text, or only file names
+    pub show_text: bool,
+    /// Requests a visitor's address may make per minute
+    pub requests_per_minute: i32,
+    /// Disabled portals answer 404
+    pub enabled: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateGuestPortalRequest {
+    /// Lowercase letters, digits and dashes, e.g. `city-council-minutes`
+    pub slug: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// Expose the documents carrying this label
+    pub label_id: Option<Uuid>,
+    /// Expose the documents of this collection and its subcollections
+    pub collection_id: Option<Uuid>,
+    /// Default true
+    pub allow_download: Option<bool>,
+    /// Default true
+    pub show_text: Option<bool>,
+    /// Default 30
+    pub requests_per_minute: Option<i32>,
+}
+
+impl CreateGuestPortalRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_slug(&self.slug)?;
+        validate_title(&self.title)?;
+        validate_description(self.description.as_deref())?;
+        if self.label_id.is_some() == self.collection_id.is_some() {
+            return Err("Expose either a label or a collection".to_string());
+        }
+        validate_requests_per_minute(self.requests_per_minute)
+    }
+}
+
+/// Fields left out are not changed. What a portal exposes cannot change; create another
+/// portal instead.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateGuestPortalRequest {
+    pub title: Option<String>,
+    /// An empty description removes it
+    pub description: Option<String>,
+    pub allow_download: Option<bool>,
+    pub show_text: Option<bool>,
+    pub requests_per_minute: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+impl UpdateGuestPortalRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(title) = &self.title {
+            validate_title(title)?;
+        }
+        validate_description(self.description.as_deref())?;
+        validate_requests_per_minute(self.requests_per_minute)
+    }
+}
+
+/// Slugs go into URLs as they are
+pub fn validate_slug(slug: &str) -> Result<(), String> {
+    let valid_chars = slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if slug.is_empty() || slug.len() > MAX_SLUG_LENGTH || !valid_chars || slug.starts_with('-') {
+        return Err(format!(
+            "The slug must be 1 to {} lowercase letters, digits and dashes, not starting with a dash",
+            MAX_SLUG_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+fn validate_title(title: &str) -> Result<(), String> {
+    if title.trim().is_empty() {
+        return Err("Title is required".to_string());
+    }
+    if title.chars().count() > MAX_TITLE_LENGTH {
+        return Err(format!("Title must be at most {} characters", MAX_TITLE_LENGTH));
+    }
+    Ok(())
+}
+
+fn validate_description(description: Option<&str>) -> Result<(), String> {
+    if description.is_some_and(|description| description.chars().count() > MAX_DESCRIPTION_LENGTH) {
+        return Err(format!("Description must be at most {} characters", MAX_DESCRIPTION_LENGTH));
+    }
+    Ok(())
+}
+
+fn validate_requests_per_minute(requests_per_minute: Option<i32>) -> Result<(), String> {
+    if requests_per_minute.is_some_and(|rpm| !(1..=MAX_GUEST_REQUESTS_PER_MINUTE).contains(&rpm)) {
+        return Err(format!("Requests per minute must be between 1 and {}", MAX_GUEST_REQUESTS_PER_MINUTE));
+    }
+    Ok(())
+}
+
+/// A portal as visitors see it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PublicGuestPortal {
+    pub slug: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub allow_download: bool,
+    pub show_text: bool,
+    pub document_count: i64,
+}
+
+impl PublicGuestPortal {
+    pub fn new(portal: GuestPortal, document_count: i64) -> Self {
+        Self {
+            slug: portal.slug,
+            title: portal.title,
+            description: portal.description,
+            allow_download: portal.allow_download,
+            show_text: portal.show_text,
+            document_count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct GuestPortalSearchQuery {
+    /// Words to find in file names, and in the text when the portal shows it
+    pub q: Option<String>,
+    /// Default 20, at most 100
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A document of a portal, without anything about its owner or where it came from
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct GuestPortalDocument {
+    pub id: Uuid,
+    pub filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GuestPortalSearchResponse {
+    pub documents: Vec<GuestPortalDocument>,
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GuestPortalDocumentDetail {
+    #[serde(flatten)]
+    pub document: GuestPortalDocument,
+    /// Only when the portal shows the text
+    pub text: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slug_validation() {
+        assert!(validate_slug("council-minutes-2024").is_ok());
+        assert!(validate_slug("").is_err());
+        assert!(validate_slug("-minutes").is_err());
+        assert!(validate_slug("Minutes").is_err());
+        assert!(validate_slug("minutes/2024").is_err());
+        assert!(validate_slug(&"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn test_portal_exposes_a_label_or_a_collection() {
+        let mut request = CreateGuestPortalRequest {
+            slug: "archive".to_string(),
+            title: "Public archive".to_string(),
+            description: None,
+            label_id: Some(Uuid::new_v4()),
+            collection_id: None,
+            allow_download: None,
+            show_text: None,
+            requests_per_minute: None,
+        };
+        assert!(request.validate().is_ok());
+        request.collection_id = Some(Uuid::new_v4());
+        assert!(request.validate().is_err());
+        request.label_id = None;
+        request.requests_per_minute = Some(0);
+        assert!(request.validate().is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+pub const DEFAULT_HOOK_TIMEOUT_SECONDS: i32 = 30;
+pub const MAX_HOOK_TIMEOUT_SECONDS: i32 = 600;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestionHookStage {
+    /// Before a file is stored; the hook may replace or reject it
+    PreConsume,
+    /// After OCR of a new document
+    PostConsume,
+}
+
+impl std::fmt::Display for IngestionHookStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IngestionHookStage::PreConsume => write!(f, "pre_consume"),
+            IngestionHookStage::PostConsume => write!(f, "post_consume"),
+        }
+    }
+}
+
+impl TryFrom<String> for IngestionHookStage {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "pre_consume" => Ok(IngestionHookStage::PreConsume),
+            "post_consume" => Ok(IngestionHookStage::PostConsume),
+            _ => Err(format!("Unknown ingestion hook stage: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestionHookKind {
+    /// An executable in `INGESTION_HOOKS_DIR`
+    Command,
+    /// An http(s) URL the file or document is posted to
+    Webhook,
+}
+
+impl std::fmt::Display for IngestionHookKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IngestionHookKind::Command => write!(f, "command"),
+            IngestionHookKind::Webhook => write!(f, "webhook"),
+        }
+    }
+}
+
+impl TryFrom<String> for IngestionHookKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "command" => Ok(IngestionHookKind::Command),
+            "webhook" => Ok(IngestionHookKind::Webhook),
+            _ => Err(format!("Unknown ingestion hook kind: {}", value)),
+        }
+    }
+}
+
+/// What happens to a file when a pre-consume hook cannot run, times out or errs.
+/// A hook that runs and rejects the file always rejects it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HookFailurePolicy {
+    Reject,
+    /// Ingest the file as it was given to the hook
+    Ignore,
+}
+
+impl std::fmt::Display for HookFailurePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookFailurePolicy::Reject => write!(f, "reject"),
+            HookFailurePolicy::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+impl TryFrom<String> for HookFailurePolicy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "reject" => Ok(HookFailurePolicy::Reject),
+            "ignore" => Ok(HookFailurePolicy::Ignore),
+            _ => Err(format!("Unknown hook failure policy: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct IngestionHook {
+    pub id: Uuid,
+    pub name: String,
+    #[sqlx(try_from = "String")]
+    pub stage: IngestionHookStage,
+    #[sqlx(try_from = "String")]
+    pub kind: IngestionHookKind,
+    /// File name of the executable for commands, URL for webhooks
+    pub target: String,
+    pub timeout_seconds: i32,
+    /// Only applies to pre-consume hooks; post-consume failures are recorded only
+    #[sqlx(try_from = "String")]
+    pub on_failure: HookFailurePolicy,
+    pub enabled: bool,
+    /// Hooks of a stage run in ascending position
+    pub position: i32,
+    /// HMAC-SHA256 key of the `X-Readur-Signature` header of webhook requests
+    pub secret: Option<String>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// Error of the most recent run, cleared when a run succeeds
+    pub last_error: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateIngestionHookRequest {
+    pub name: String,
+    pub stage: IngestionHookStage,
+    pub kind: IngestionHookKind,
+    pub target: String,
+    /// Defaults to 30 seconds, at most 600
+    pub timeout_seconds: Option<i32>,
+    /// Defaults to `reject`
+    pub on_failure: Option<HookFailurePolicy>,
+    /// Defaults to true
+    pub enabled: Option<bool>,
+    /// Defaults to 0
+    pub position: Option<i32>,
+}
+
+impl CreateIngestionHookRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name)?;
+        validate_target(self.kind, &self.target)?;
+        validate_timeout(self.timeout_seconds)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateIngestionHookRequest {
+    pub name: Option<String>,
+    /// Checked against the hook's kind, which cannot change
+    pub target: Option<String>,
+    pub timeout_seconds: Option<i32>,
+    pub on_failure: Option<HookFailurePolicy>,
+    pub enabled: Option<bool>,
+    pub position: Option<i32>,
+}
+
+impl UpdateIngestionHookRequest {
+    pub fn validate(&self, kind: IngestionHookKind) -> Result<(), String> {
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        if let Some(target) = &self.target {
+            validate_target(kind, target)?;
+        }
+        validate_timeout(self.timeout_seconds)
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Name must not be empty".to_string());
+    }
+    Ok(())
+}
+
+/// Commands are named by their file name in the hooks directory, so a hook can never
+/// run a program outside it
+fn validate_target(kind: IngestionHookKind, target: &str) -> Result<(), String> {
+    match kind {
+        IngestionHookKind::Command => {
+            let valid = !target.is_empty()
+                && !target.starts_with('.')
+                && target.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+            if !valid {
+                return Err(format!("'{}' is not the file name of an executable in the hooks directory", target));
+            }
+        }
+        IngestionHookKind::Webhook => {
+            let url = url::Url::parse(target).map_err(|e| format!("Invalid URL '{}': {}", target, e))?;
+            if url.scheme() != "http" && url.scheme() != "https" {
+                return Err(format!("Webhook URL '{}' must use http or https", target));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_timeout(timeout_seconds: Option<i32>) -> Result<(), String> {
+    match timeout_seconds {
+        Some(timeout) if !(1..=MAX_HOOK_TIMEOUT_SECONDS).contains(&timeout) => {
+            Err(format!("Timeout must be between 1 and {} seconds", MAX_HOOK_TIMEOUT_SECONDS))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(kind: IngestionHookKind, target: &str) -> CreateIngestionHookRequest {
+        CreateIngestionHookRequest {
+            name: "hook".to_string(),
+            stage: IngestionHookStage::PreConsume,
+            kind,
+            target: target.to_string(),
+            timeout_seconds: None,
+            on_failure: None,
+            enabled: None,
+            position: None,
+        }
+    }
+
+    #[test]
+    fn test_command_targets_stay_in_hooks_directory() {
+        assert!(request(IngestionHookKind::Command, "convert-heic.sh").validate().is_ok());
+        assert!(request(IngestionHookKind::Command, "../bin/sh").validate().is_err());
+        assert!(request(IngestionHookKind::Command, "/usr/bin/env").validate().is_err());
+        assert!(request(IngestionHookKind::Command, ".hidden").validate().is_err());
+        assert!(request(IngestionHookKind::Command, "").validate().is_err());
+    }
+
+    #[test]
+    fn test_webhook_targets_are_http_urls() {
+        assert!(request(IngestionHookKind::Webhook, "https://hooks.example.com/consume").validate().is_ok());
+        assert!(request(IngestionHookKind::Webhook, "ftp://example.com").validate().is_err());
+        assert!(request(IngestionHookKind::Webhook, "not a url").validate().is_err());
+    }
+
+    #[test]
+    fn test_timeout_bounds() {
+        let mut hook = request(IngestionHookKind::Command, "check.sh");
+        hook.timeout_seconds = Some(0);
+        assert!(hook.validate().is_err());
+        hook.timeout_seconds = Some(MAX_HOOK_TIMEOUT_SECONDS + 1);
+        assert!(hook.validate().is_err());
+        hook.timeout_seconds = Some(MAX_HOOK_TIMEOUT_SECONDS);
+        assert!(hook.validate().is_ok());
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Name shown when the admin set none
+pub const DEFAULT_INSTANCE_NAME: &str = "Readur";
+const MAX_NAME_LENGTH: usize = 100;
+const MAX_LOGO_URL_LENGTH: usize = 2048;
+const MAX_LOGIN_MESSAGE_LENGTH: usize = 2000;
+
+/// Branding and defaults of this instance as the admin saved them; unset fields use the
+/// built-in ones
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct InstanceSettings {
+    pub name: Option<String>,
+    /// An `http(s)` URL, or a path on this server such as `/branding/logo.svg`
+    pub logo_url: Option<String>,
+    /// Hex color such as `#0969da`
+    pub accent_color: Option<String>,
+    /// Shown above the login form, e.g. a usage notice
+    pub login_message: Option<String>,
+    /// Locale of users who chose none, before `DEFAULT_LOCALE`
+    pub default_language: Option<String>,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// The whole set of settings; fields left out or empty go back to the built-in ones
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateInstanceSettingsRequest {
+    pub name: Option<String>,
+    pub logo_url: Option<String>,
+    pub accent_color: Option<String>,
+    pub login_message: Option<String>,
+    /// A locale with a translation catalog, see `GET /api/i18n/locales`
+    pub default_language: Option<String>,
+}
+
+impl UpdateInstanceSettingsRequest {
+    /// Trim the fields and drop empty ones
+    pub fn normalized(self) -> Self {
+        let clean = |value: Option<String>| {
+            value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+        };
+        Self {
+            name: clean(self.name),
+            logo_url: clean(self.logo_url),
+            accent_color: clean(self.accent_color).map(|color| color.to_ascii_lowercase()),
+            login_message: clean(self.login_message),
+            default_language: clean(self.default_language),
+        }
+    }
+
+    /// Checks everything but whether there is a catalog for the default language
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.as_ref().is_some_and(|name| name.chars().count() > MAX_NAME_LENGTH) {
+            return Err(format!("Name must be at most {} characters", MAX_NAME_LENGTH));
+        }
+        if let Some(url) = &self.logo_url {
+            validate_logo_url(url)?;
+        }
+        if let Some(color) = &self.accent_color {
+            validate_accent_color(color)?;
+        }
+        if self
+            .login_message
+            .as_ref()
+            .is_some_and(|message| message.chars().count() > MAX_LOGIN_MESSAGE_LENGTH)
+        {
+            return Err(format!("Login message must be at most {} characters", MAX_LOGIN_MESSAGE_LENGTH));
+        }
+        Ok(())
+    }
+}
+
+/// Logos are loaded by the login page, so only web URLs and paths of this server
+fn validate_logo_url(url: &str) -> Result<(), String> {
+    let is_web_url = url.starts_with("https://") || url.starts_with("http://");
+    let is_local_path = url.starts_with('/') && !url.starts_with("//");
+    if url.len() > MAX_LOGO_URL_LENGTH || !(is_web_url || is_local_path) || url.chars().any(char::is_whitespace) {
+        return Err("Logo URL must be an http(s) URL or a path starting with /".to_string());
+    }
+    Ok(())
+}
+
+fn validate_accent_color(color: &str) -> Result<(), String> {
+    let is_hex_color = color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if !is_hex_color {
+        return Err("Accent color must look like #0969da".to_string());
+    }
+    Ok(())
+}
+
+/// What the login page shows, with the built-in values filled in
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InstanceInfo {
+    pub name: String,
+    pub logo_url: Option<String>,
+    pub accent_color: Option<String>,
+    pub login_message: Option<String>,
+    /// The locale of users who chose none
+    pub default_language: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_drops_empty_fields() {
+        let request = UpdateInstanceSettingsRequest {
+            name: Some("  Records Office ".to_string()),
+            accent_color: Some("#0969DA".to_string()),
+            login_message: Some("   ".to_string()),
+            ..Default::default()
+        }
+        .normalized();
+
+        assert_eq!(request.name.as_deref(), Some("Records Office"));
+        assert_eq!(request.accent_color.as_deref(), Some("#0969da"));
+        assert_eq!(request.login_message, None);
+    }
+
+    #[test]
+    fn test_logo_url_validation() {
+        assert!(validate_logo_url("https://example.com/logo.png").is_ok());
+        assert!(validate_logo_url("/branding/logo.svg").is_ok());
+        assert!(validate_logo_url("//example.com/logo.png").is_err());
+        assert!(validate_logo_url("javascript:alert(1)").is_err());
+        assert!(validate_logo_url("data:image/png;base64,AAAA").is_err());
+    }
+
+    #[test]
+    fn test_accent_color_validation() {
+        assert!(validate_accent_color("#0969da").is_ok());
+        assert!(validate_accent_color("#fff").is_err());
+        assert!(validate_accent_color("blue").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use utoipa::{IntoParams, ToSchema};
+
+use super::{CustomFieldDefinition, DocumentCustomFields};
+
+/// Document type that extracted invoice data is stored under, created per user on first use
+pub const INVOICE_DOCUMENT_TYPE: &str = "Invoice";
+
+/// `auto` asks the LLM when one is configured and otherwise applies the label rules
+pub const INVOICE_EXTRACTION_MODES: [&str; 3] = ["auto", "llm", "rules"];
+
+/// One position of an invoice
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InvoiceLineItem {
+    pub description: String,
+    pub quantity: Option<f64>,
+    pub unit_price: Option<f64>,
+    pub amount: Option<f64>,
+}
+
+/// Structured data of an invoice; anything not found is left empty
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InvoiceData {
+    pub vendor: Option<String>,
+    pub invoice_number: Option<String>,
+    pub invoice_date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    /// Gross total
+    pub total: Option<f64>,
+    pub tax: Option<f64>,
+    /// ISO 4217 code, e.g. `EUR`
+    pub currency: Option<String>,
+    pub line_items: Vec<InvoiceLineItem>,
+}
+
+impl InvoiceData {
+    /// Whether nothing but perhaps the vendor was found
+    pub fn is_empty(&self) -> bool {
+        self.invoice_number.is_none()
+            && self.invoice_date.is_none()
+            && self.due_date.is_none()
+            && self.total.is_none()
+            && self.tax.is_none()
+            && self.line_items.is_empty()
+    }
+
+    /// The values of the fields of `invoice_field_definitions` that were found
+    pub fn to_custom_fields(&self) -> Map<String, Value> {
+        let mut fields = Map::new();
+        let mut insert = |name: &str, value: Value| {
+            if !value.is_null() {
+                fields.insert(name.to_string(), value);
+            }
+        };
+        insert("vendor", json!(self.vendor));
+        insert("invoice_number", json!(self.invoice_number));
+        insert("invoice_date", json!(self.invoice_date.map(|date| date.to_string())));
+        insert("due_date", json!(self.due_date.map(|date| date.to_string())));
+        insert("total", json!(self.total));
+        insert("tax", json!(self.tax));
+        insert("currency", json!(self.currency));
+        if !self.line_items.is_empty() {
+            insert("line_items", json!(self.line_items));
+        }
+        fields
+    }
+}
+
+/// Custom fields of the `Invoice` document type
+pub fn invoice_field_definitions() -> Vec<CustomFieldDefinition> {
+    [
+        ("vendor", "text"),
+        ("invoice_number", "text"),
+        ("invoice_date", "date"),
+        ("due_date", "date"),
+        ("total", "amount"),
+        ("tax", "amount"),
+        ("currency", "currency"),
+        ("line_items", "line_items"),
+    ]
+    .into_iter()
+    .map(|(name, field_type)| CustomFieldDefinition {
+        name: name.to_string(),
+        field_type: field_type.to_string(),
+        required: false,
+    })
+    .collect()
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct InvoiceExtractionQuery {
+    /// `auto` (default), `llm` or `rules`
+    pub mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InvoiceExtractionResponse {
+    /// `llm` or `rules`, whichever produced the data
+    pub extracted_by: String,
+    pub invoice: InvoiceData,
+    /// The document's `Invoice` custom fields after merging in the extracted values
+    pub custom_fields: DocumentCustomFields,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct InvoiceExportQuery {
+    /// First invoice date to include
+    pub from: Option<NaiveDate>,
+    /// Last invoice date to include
+    pub to: Option<NaiveDate>,
+    /// One row per line item instead of one per invoice
+    #[serde(default)]
+    pub line_items: bool,
+}
@@ -0,0 +1,282 @@
+use chrono::{DateTime, Utc};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::correspondent::contains_words;
+
+/// Most keywords, sources or MIME types in one rule
+pub const MAX_RULE_VALUES: usize = 50;
+
+/// Conditions under which a label is applied to documents automatically. Every
+/// condition that is set must hold; within `sources` and `mime_types` any may match.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LabelRule {
+    pub label_id: Uuid,
+    pub user_id: Uuid,
+    /// Words or phrases that occur in the text as whole words: any of them, or all
+    /// with `match_all_keywords`
+    pub keywords: Vec<String>,
+    pub match_all_keywords: bool,
+    /// Regular expression that matches the text
+    pub regex: Option<String>,
+    /// Source names or types, or how the document arrived such as `web_upload`
+    pub sources: Vec<String>,
+    /// MIME types such as `application/pdf`, or wildcards such as `image/*`
+    pub mime_types: Vec<String>,
+    /// Whether keywords and the regular expression match case-sensitively
+    pub case_sensitive: bool,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetLabelRuleRequest {
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub match_all_keywords: bool,
+    pub regex: Option<String>,
+    #[serde(default)]
+    pub sources: Vec<String>,
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Default true
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct ApplyLabelRulesQuery {
+    /// Only apply this label's rule
+    pub label_id: Option<Uuid>,
+}
+
+/// Result of applying label rules to existing documents
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApplyLabelRulesResponse {
+    pub documents_checked: i64,
+    pub labels_assigned: i64,
+}
+
+/// What a label rule is checked against
+#[derive(Debug, Clone, Default)]
+pub struct LabelRuleSubject<'a> {
+    /// OCR text or content; `None` before OCR, when rules on the text do not match yet
+    pub text: Option<&'a str>,
+    pub mime_type: &'a str,
+    /// How the document arrived, and the name and type of its source
+    pub sources: Vec<String>,
+}
+
+impl SetLabelRuleRequest {
+    /// The request with values trimmed and empty ones dropped, or what is wrong with it
+    pub fn normalize(self) -> Result<Self, String> {
+        let clean = |values: Vec<String>| -> Vec<String> {
+            values
+                .iter()
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let request = Self {
+            keywords: clean(self.keywords),
+            regex: self.regex.map(|regex| regex.trim().to_string()).filter(|regex| !regex.is_empty()),
+            sources: clean(self.sources),
+            mime_types: clean(self.mime_types)
+                .into_iter()
+                .map(|mime_type| mime_type.to_ascii_lowercase())
+                .collect(),
+            ..self
+        };
+
+        if request.keywords.is_empty()
+            && request.regex.is_none()
+            && request.sources.is_empty()
+            && request.mime_types.is_empty()
+        {
+            return Err("A rule needs keywords, a regex, sources or MIME types".to_string());
+        }
+        for (field, values) in [
+            ("keywords", &request.keywords),
+            ("sources", &request.sources),
+            ("mime_types", &request.mime_types),
+        ] {
+            if values.len() > MAX_RULE_VALUES {
+                return Err(format!("At most {} {} are allowed", MAX_RULE_VALUES, field));
+            }
+        }
+        if let Some(mime_type) = request.mime_types.iter().find(|mime_type| !mime_type.contains('/')) {
+            return Err(format!("'{}' is not a MIME type", mime_type));
+        }
+        if let Some(regex) = &request.regex {
+            RegexBuilder::new(regex)
+                .size_limit(1 << 20)
+                .build()
+                .map_err(|e| format!("Invalid regular expression: {}", e))?;
+        }
+
+        Ok(request)
+    }
+}
+
+impl LabelRule {
+    /// Whether the rule looks at the text, and so can only match after OCR
+    pub fn needs_text(&self) -> bool {
+        !self.keywords.is_empty() || self.regex.is_some()
+    }
+
+    pub fn matches(&self, subject: &LabelRuleSubject) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if !self.mime_types.is_empty()
+            && !self.mime_types.iter().any(|pattern| mime_type_matches(pattern, subject.mime_type))
+        {
+            return false;
+        }
+        if !self.sources.is_empty()
+            && !self
+                .sources
+                .iter()
+                .any(|source| subject.sources.iter().any(|name| name.eq_ignore_ascii_case(source)))
+        {
+            return false;
+        }
+        if !self.needs_text() {
+            return true;
+        }
+
+        let Some(text) = subject.text else {
+            return false;
+        };
+        self.keywords_match(text) && self.regex_matches(text)
+    }
+
+    fn keywords_match(&self, text: &str) -> bool {
+        if self.keywords.is_empty() {
+            return true;
+        }
+        let text = if self.case_sensitive { text.to_string() } else { text.to_lowercase() };
+        let occurs = |keyword: &String| {
+            if self.case_sensitive {
+                contains_words(&text, keyword)
+            } else {
+                contains_words(&text, &keyword.to_lowercase())
+            }
+        };
+        if self.match_all_keywords {
+            self.keywords.iter().all(occurs)
+        } else {
+            self.keywords.iter().any(occurs)
+        }
+    }
+
+    fn regex_matches(&self, text: &str) -> bool {
+        self.regex.as_deref().is_none_or(|regex| {
+            RegexBuilder::new(regex)
+                .case_insensitive(!self.case_sensitive)
+                .size_limit(1 << 20)
+                .build()
+                .is_ok_and(|regex| regex.is_match(text))
+        })
+    }
+}
+
+/// Whether a MIME type is the pattern, or of the type of a pattern such as `image/*`
+fn mime_type_matches(pattern: &str, mime_type: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(kind) => mime_type.split('/').next().is_some_and(|t| t.eq_ignore_ascii_case(kind)),
+        None => pattern.eq_ignore_ascii_case(mime_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(request: SetLabelRuleRequest) -> LabelRule {
+        let request = request.normalize().unwrap();
+        LabelRule {
+            label_id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            keywords: request.keywords,
+            match_all_keywords: request.match_all_keywords,
+            regex: request.regex,
+            sources: request.sources,
+            mime_types: request.mime_types,
+            case_sensitive: request.case_sensitive,
+            enabled: request.enabled.unwrap_or(true),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn request() -> SetLabelRuleRequest {
+        SetLabelRuleRequest {
+            keywords: Vec::new(),
+            match_all_keywords: false,
+            regex: None,
+            sources: Vec::new(),
+            mime_types: Vec::new(),
+            case_sensitive: false,
+            enabled: None,
+        }
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let text = "Tax Assessment 2024\nReference: ST-4711-2024";
+        let pdf = LabelRuleSubject {
+            text: Some(text),
+            mime_type: "application/pdf",
+            sources: vec!["webdav".to_string(), "Office NAS".to_string()],
+        };
+
+        let taxes = rule(SetLabelRuleRequest {
+            keywords: vec!["tax assessment".to_string(), " ".to_string()],
+            mime_types: vec!["application/*".to_string()],
+            ..request()
+        });
+        assert!(taxes.matches(&pdf));
+        assert!(!taxes.matches(&LabelRuleSubject { text: None, ..pdf.clone() }));
+        assert!(!taxes.matches(&LabelRuleSubject { mime_type: "image/png", ..pdf.clone() }));
+
+        let all = rule(SetLabelRuleRequest {
+            keywords: vec!["tax".to_string(), "invoice".to_string()],
+            match_all_keywords: true,
+            ..request()
+        });
+        assert!(!all.matches(&pdf));
+
+        let reference = rule(SetLabelRuleRequest {
+            regex: Some(r"ST-\d{4}-\d{4}".to_string()),
+            sources: vec!["office nas".to_string()],
+            ..request()
+        });
+        assert!(reference.matches(&pdf));
+        assert!(!reference.matches(&LabelRuleSubject { sources: vec!["web_upload".to_string()], ..pdf.clone() }));
+
+        let scans = rule(SetLabelRuleRequest { mime_types: vec!["image/*".to_string()], ..request() });
+        assert!(!scans.needs_text());
+        assert!(scans.matches(&LabelRuleSubject { text: None, mime_type: "image/jpeg", sources: Vec::new() }));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert!(request().normalize().is_err());
+        assert!(SetLabelRuleRequest { keywords: vec![" ".to_string()], ..request() }.normalize().is_err());
+        assert!(SetLabelRuleRequest { regex: Some("(unclosed".to_string()), ..request() }.normalize().is_err());
+        assert!(SetLabelRuleRequest { mime_types: vec!["pdf".to_string()], ..request() }.normalize().is_err());
+
+        let normalized = SetLabelRuleRequest { mime_types: vec![" Image/PNG ".to_string()], ..request() }
+            .normalize()
+            .unwrap();
+        assert_eq!(normalized.mime_types, ["image/png"]);
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use super::MAX_BULK_DOCUMENTS;
+
+/// A hold placed on documents for a matter such as a lawsuit or an audit. While it is
+/// active its documents cannot be deleted, renamed, given to another owner or have
+/// their contents replaced, whatever the role of the user asking.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LegalHold {
+    pub id: Uuid,
+    /// The matter the documents are held for
+    pub name: String,
+    pub reason: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// When the hold was lifted; None while it is active
+    pub released_at: Option<DateTime<Utc>>,
+    pub released_by: Option<Uuid>,
+    pub document_count: i64,
+}
+
+impl LegalHold {
+    pub fn is_active(&self) -> bool {
+        self.released_at.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateLegalHold {
+    pub name: String,
+    pub reason: Option<String>,
+    /// Documents to hold right away
+    #[serde(default)]
+    pub document_ids: Vec<Uuid>,
+}
+
+impl CreateLegalHold {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Name is required".to_string());
+        }
+        if self.document_ids.len() > MAX_BULK_DOCUMENTS {
+            return Err(format!("At most {} documents can be added at once", MAX_BULK_DOCUMENTS));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LegalHoldDocumentsRequest {
+    pub document_ids: Vec<Uuid>,
+}
+
+impl LegalHoldDocumentsRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.document_ids.is_empty() {
+            return Err("No documents given".to_string());
+        }
+        if self.document_ids.len() > MAX_BULK_DOCUMENTS {
+            return Err(format!("At most {} documents can be added at once", MAX_BULK_DOCUMENTS));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LegalHoldDocumentsResponse {
+    /// Documents newly put on the hold
+    pub added: Vec<Uuid>,
+    /// Documents that were already on it
+    pub already_held: Vec<Uuid>,
+    /// Documents that do not exist
+    pub not_found: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct LegalHoldManifestQuery {
+    /// `json` (default) or `csv`
+    pub format: Option<String>,
+    /// Read every file back from storage and check it still has the recorded hash
+    /// (default: false)
+    #[serde(default)]
+    pub verify: bool,
+}
+
+/// One stored file of a held document: its current contents or an earlier version
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ManifestEntry {
+    pub document_id: Uuid,
+    /// None for the current contents
+    pub version_number: Option<i32>,
+    pub filename: String,
+    pub original_filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    /// SHA-256 of the file, recorded when it was stored
+    pub file_hash: Option<String>,
+    /// When the file was stored
+    pub stored_at: DateTime<Utc>,
+    /// When newer contents replaced these; None for the current contents
+    pub superseded_at: Option<DateTime<Utc>>,
+    pub added_to_hold_at: DateTime<Utc>,
+    /// Whether the file read back from storage has the recorded hash; None when not verified
+    pub verified: Option<bool>,
+}
+
+/// The hashes and timestamps of every file of every document on a hold, to show that
+/// the documents were not altered while they were held
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LegalHoldManifest {
+    pub hold: LegalHold,
+    pub generated_at: DateTime<Utc>,
+    pub generated_by: Uuid,
+    pub entries: Vec<ManifestEntry>,
+    /// SHA-256 of the CSV form of the entries, so a copy of the manifest can be checked
+    /// against the one that was exported
+    pub manifest_hash: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_legal_hold_validation() {
+        let request = CreateLegalHold { name: "Smith v. ACME".to_string(), reason: None, document_ids: vec![] };
+        assert!(request.validate().is_ok());
+
+        let unnamed = CreateLegalHold { name: "  ".to_string(), ..request.clone() };
+        assert!(unnamed.validate().is_err());
+
+        let too_many = CreateLegalHold { document_ids: vec![Uuid::new_v4(); MAX_BULK_DOCUMENTS + 1], ..request };
+        assert!(too_many.validate().is_err());
+
+        assert!(LegalHoldDocumentsRequest { document_ids: vec![] }.validate().is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// The virtual folders at the top of the WebDAV library
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibraryGrouping {
+    /// A folder per label
+    Labels,
+    /// A folder per correspondent
+    Correspondents,
+    /// A folder per year the documents were created in
+    Years,
+    /// Every document, without subfolders
+    All,
+}
+
+impl LibraryGrouping {
+    pub const ALL: [LibraryGrouping; 4] = [
+        LibraryGrouping::Labels,
+        LibraryGrouping::Correspondents,
+        LibraryGrouping::Years,
+        LibraryGrouping::All,
+    ];
+
+    /// Name of the grouping's folder
+    pub fn folder_name(self) -> &'static str {
+        match self {
+            LibraryGrouping::Labels => "Labels",
+            LibraryGrouping::Correspondents => "Correspondents",
+            LibraryGrouping::Years => "Years",
+            LibraryGrouping::All => "All Documents",
+        }
+    }
+
+    pub fn from_folder_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|grouping| grouping.folder_name() == name)
+    }
+}
+
+/// The documents one folder of the WebDAV library holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibraryFolder {
+    Label(Uuid),
+    Correspondent(Uuid),
+    Year(i32),
+    All,
+}
+
+/// A folder under a grouping, with the last change of a document in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryFolderEntry {
+    pub folder: LibraryFolder,
+    pub name: String,
+    pub modified_at: DateTime<Utc>,
+}
+
+/// A document as a file of the WebDAV library
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct LibraryFile {
+    pub id: Uuid,
+    pub original_filename: String,
+    pub file_size: i64,
+    pub mime_type: String,
+    pub file_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
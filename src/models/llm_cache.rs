@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// Cached LLM answers of one prompt version and model
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LlmCacheStats {
+    /// Prompt the answers are for, e.g. `graph_extraction`, `pii` or `translation`
+    pub task: String,
+    pub prompt_version: i32,
+    pub model: String,
+    pub entries: i64,
+    /// Requests answered from the cache instead of the model
+    pub hits: i64,
+    /// Size of the cached answers
+    pub response_bytes: i64,
+    pub last_hit_at: Option<DateTime<Utc>>,
+    pub newest_entry_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct InvalidateLlmCacheQuery {
+    /// Prompt whose cached answers are removed
+    pub task: String,
+    /// Only answers to this version of the prompt; all versions when not given
+    pub prompt_version: Option<i32>,
+    /// Only answers of this model
+    pub model: Option<String>,
+}
+
+impl InvalidateLlmCacheQuery {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.task.trim().is_empty() {
+            return Err("A task is required".to_string());
+        }
+        if self.prompt_version.is_some_and(|version| version < 1) {
+            return Err("Prompt versions start at 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InvalidateLlmCacheResponse {
+    pub deleted: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct AnalyzeDocumentQuery {
+    /// Ask the model again even when it answered for the same text before
+    #[serde(default)]
+    pub bypass_cache: bool,
+    /// Log the prompt and answer to the LLM debug log even when it is off (admins only)
+    #[serde(default)]
+    pub debug: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalidate_query_validation() {
+        let query = |task: &str, prompt_version: Option<i32>| InvalidateLlmCacheQuery {
+            task: task.to_string(),
+            prompt_version,
+            model: None,
+        };
+        assert!(query("pii", None).validate().is_ok());
+        assert!(query("graph_extraction", Some(1)).validate().is_ok());
+        assert!(query(" ", None).validate().is_err());
+        assert!(query("pii", Some(0)).validate().is_err());
+    }
+}
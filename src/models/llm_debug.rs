@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use super::PiiKind;
+
+pub const DEFAULT_LLM_DEBUG_RUNS_LIMIT: i64 = 50;
+pub const MAX_LLM_DEBUG_RUNS_LIMIT: i64 = 500;
+
+/// One analysis run in the LLM debug log
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LlmDebugRun {
+    pub id: Uuid,
+    pub document_id: Option<Uuid>,
+    /// Prompt the run is for, e.g. `graph_extraction`
+    pub task: String,
+    pub model: String,
+    /// Graph version the run produced
+    pub graph_version_id: Option<Uuid>,
+    /// Why the run failed, such as an answer that is not a graph
+    pub error: Option<String>,
+    /// Kinds of personal data redacted from the stored text, or `none`
+    pub redaction: String,
+    pub started_at: DateTime<Utc>,
+    /// None while the run is going on
+    pub finished_at: Option<DateTime<Utc>>,
+    pub exchange_count: i64,
+}
+
+/// A request of a run to the LLM and its answer, redacted as configured
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LlmDebugExchange {
+    pub id: Uuid,
+    pub prompt_version: i32,
+    pub system_prompt: String,
+    pub prompt: String,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    /// Answered from the response cache instead of the model
+    pub cached: bool,
+    pub duration_ms: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LlmDebugRunDetail {
+    #[serde(flatten)]
+    pub run: LlmDebugRun,
+    /// Oldest first
+    pub exchanges: Vec<LlmDebugExchange>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct ListLlmDebugRunsQuery {
+    pub document_id: Option<Uuid>,
+    /// Only runs that failed
+    #[serde(default)]
+    pub failed_only: bool,
+    /// Most runs returned, newest first (default 50, at most 500)
+    pub limit: Option<i64>,
+}
+
+impl ListLlmDebugRunsQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_LLM_DEBUG_RUNS_LIMIT).clamp(1, MAX_LLM_DEBUG_RUNS_LIMIT)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct ClearLlmDebugRunsQuery {
+    /// Only runs of this document; all runs when not given
+    pub document_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClearLlmDebugRunsResponse {
+    pub deleted: u64,
+}
+
+/// How the LLM debug log is configured
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LlmDebugLogStatus {
+    /// Whether every analysis run is logged; admins can log single runs either way
+    pub enabled: bool,
+    /// Kinds of personal data replaced in the stored prompts and answers
+    pub redacted_kinds: Vec<PiiKind>,
+    /// Days runs are kept
+    pub retention_days: i64,
+    /// Runs in the log
+    pub runs: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_limit() {
+        assert_eq!(ListLlmDebugRunsQuery::default().limit(), DEFAULT_LLM_DEBUG_RUNS_LIMIT);
+        let query = ListLlmDebugRunsQuery { limit: Some(10_000), ..Default::default() };
+        assert_eq!(query.limit(), MAX_LLM_DEBUG_RUNS_LIMIT);
+        let query = ListLlmDebugRunsQuery { limit: Some(0), ..Default::default() };
+        assert_eq!(query.limit(), 1);
+    }
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Metered LLM feature name for vision transcription of poor scans, counted in pages
+pub const LLM_FEATURE_VISION_OCR: &str = "vision_ocr";
+/// Metered LLM feature name for knowledge-graph extraction, counted in documents
+pub const LLM_FEATURE_GRAPH_EXTRACTION: &str = "graph_extraction";
+/// Metered LLM feature name for document translation, counted in requests
+pub const LLM_FEATURE_TRANSLATION: &str = "translation";
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct VisionFallbackPage {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub page_number: i32,
+    pub model: String,
+    pub ocr_confidence: Option<f32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LlmQuotaStatus {
+    pub feature: String,
+    /// Units consumed since the start of the current UTC day
+    pub used_today: i64,
+    /// Daily allowance, `None` when the feature is unlimited
+    pub daily_limit: Option<i64>,
+    pub remaining_today: Option<i64>,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A locale server-generated text can be written in
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LocaleInfo {
+    /// Such as `de` or `pt-BR`
+    pub code: String,
+    /// The catalog's `locale.name`, e.g. `Deutsch`
+    pub name: String,
+    pub keys: usize,
+    /// English texts the catalog lacks, which are sent in English
+    pub missing_keys: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserLocaleResponse {
+    /// The locale the user chose; null follows the server's default
+    pub locale: Option<String>,
+    /// The locale their notifications, digests and error messages are written in
+    pub effective_locale: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateUserLocaleRequest {
+    /// A locale there is a catalog for, or its region such as `de-AT`; null for the
+    /// server's default
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReloadCatalogsResponse {
+    pub locales: Vec<LocaleInfo>,
+    /// Catalog files that were skipped, and why
+    pub errors: Vec<String>,
+}
@@ -7,6 +7,91 @@ pub mod settings;
 pub mod source;
 pub mod source_error;
 pub mod responses;
+pub mod event;
+pub mod page_artifact;
+pub mod llm_usage;
+pub mod llm_cache;
+pub mod llm_debug;
+pub mod outbound_limit;
+pub mod data_residency;
+pub mod document_split;
+pub mod form;
+pub mod consistency;
+pub mod attachment;
+pub mod storage_migration;
+pub mod storage_layout;
+pub mod webhook;
+pub mod encryption;
+pub mod physical_location;
+pub mod client_sync;
+pub mod similar_document;
+pub mod document_version;
+pub mod retention_policy;
+pub mod audit;
+pub mod sharing;
+pub mod share_link;
+pub mod api_token;
+pub mod two_factor;
+pub mod passkey;
+pub mod session;
+pub mod saved_search;
+pub mod correspondent;
+pub mod document_type;
+pub mod invoice;
+pub mod label_rule;
+pub mod bulk_operation;
+pub mod notification_channel;
+pub mod export;
+pub mod backup;
+pub mod upload;
+pub mod file_blob;
+pub mod quarantine;
+pub mod workspace;
+pub mod storage_quota;
+pub mod annotation;
+pub mod redaction;
+pub mod pii;
+pub mod calendar;
+pub mod reminder;
+pub mod workflow;
+pub mod pdf_security;
+pub mod pdf_signature;
+pub mod legal_hold;
+pub mod search_analyzer;
+pub mod analysis_batch;
+pub mod document_graph;
+pub mod entity;
+pub mod timeline;
+pub mod ocr_comparison;
+pub mod ocr_correction;
+pub mod ocr_word;
+pub mod statistics;
+pub mod digest;
+pub mod document_relation;
+pub mod collection;
+pub mod translation;
+pub mod audio;
+pub mod ingestion_hook;
+pub mod failed_document;
+pub mod document_progress;
+pub mod library_dav;
+pub mod scan_bridge;
+pub mod scan_device;
+pub mod omr;
+pub mod thumbnail_job;
+pub mod storage_compression;
+pub mod worker_node;
+pub mod processing_usage;
+pub mod guest_portal;
+pub mod instance;
+pub mod client_preference;
+pub mod document_access;
+pub mod document_title;
+pub mod embargo;
+pub mod page_transform;
+pub mod locale;
+pub mod operation_controls;
+pub mod document_expiration;
 
 // Re-export commonly used types
 pub use user::*;
@@ -15,5 +100,90 @@ pub use search::*;
 pub use settings::*;
 pub use source::*;
 pub use source_error::*;
+pub use event::*;
+pub use page_artifact::*;
+pub use llm_usage::*;
+pub use llm_cache::*;
+pub use llm_debug::*;
+pub use outbound_limit::*;
+pub use data_residency::*;
+pub use document_split::*;
+pub use form::*;
+pub use consistency::*;
+pub use attachment::*;
+pub use storage_migration::*;
+pub use storage_layout::*;
+pub use webhook::*;
+pub use encryption::*;
+pub use physical_location::*;
+pub use client_sync::*;
+pub use similar_document::*;
+pub use document_version::*;
+pub use retention_policy::*;
+pub use audit::*;
+pub use sharing::*;
+pub use share_link::*;
+pub use api_token::*;
+pub use two_factor::*;
+pub use passkey::*;
+pub use session::*;
+pub use saved_search::*;
+pub use correspondent::*;
+pub use document_type::*;
+pub use invoice::*;
+pub use label_rule::*;
+pub use bulk_operation::*;
+pub use notification_channel::*;
+pub use export::*;
+pub use backup::*;
+pub use upload::*;
+pub use file_blob::*;
+pub use quarantine::*;
+pub use workspace::*;
+pub use storage_quota::*;
+pub use annotation::*;
+pub use redaction::*;
+pub use pii::*;
+pub use calendar::*;
+pub use reminder::*;
+pub use workflow::*;
+pub use pdf_security::*;
+pub use pdf_signature::*;
+pub use legal_hold::*;
+pub use search_analyzer::*;
+pub use analysis_batch::*;
+pub use document_graph::*;
+pub use entity::*;
+pub use timeline::*;
+pub use ocr_comparison::*;
+pub use ocr_correction::*;
+pub use ocr_word::*;
+pub use statistics::*;
+pub use digest::*;
+pub use document_relation::*;
+pub use collection::*;
+pub use translation::*;
+pub use audio::*;
+pub use ingestion_hook::*;
+pub use failed_document::*;
+pub use document_progress::*;
+pub use library_dav::*;
+pub use scan_bridge::*;
+pub use scan_device::*;
+pub use omr::*;
+pub use thumbnail_job::*;
+pub use storage_compression::*;
+pub use worker_node::*;
+pub use processing_usage::*;
+pub use guest_portal::*;
+pub use instance::*;
+pub use client_preference::*;
+pub use document_access::*;
+pub use document_title::*;
+pub use embargo::*;
+pub use page_transform::*;
+pub use locale::*;
+pub use operation_controls::*;
+pub use document_expiration::*;
 
 pub use responses::*;
\ No newline at end of file
@@ -0,0 +1,211 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Types of in-app notifications a channel can opt into
+pub const NOTIFICATION_TYPES: [&str; 4] = ["success", "error", "info", "warning"];
+
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Where a channel sends notifications, tagged by `type`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationChannelConfig {
+    /// Sent through the server's SMTP settings
+    Email {
+        /// Defaults to the user's email address
+        #[serde(default)]
+        to: Option<String>,
+    },
+    Ntfy {
+        /// Defaults to https://ntfy.sh
+        #[serde(default)]
+        server_url: Option<String>,
+        topic: String,
+        /// Access token of a protected topic
+        #[serde(default)]
+        token: Option<String>,
+    },
+    Gotify {
+        server_url: String,
+        /// Application token
+        token: String,
+    },
+    Slack {
+        /// Incoming webhook URL
+        webhook_url: String,
+    },
+}
+
+impl NotificationChannelConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            NotificationChannelConfig::Email { to } => {
+                if to.as_deref().is_some_and(|to| !is_email_address(to)) {
+                    return Err("to is not an email address".to_string());
+                }
+            }
+            NotificationChannelConfig::Ntfy { server_url, topic, token } => {
+                if server_url.as_deref().is_some_and(|url| !is_http_url(url)) {
+                    return Err("server_url must be an http(s) URL".to_string());
+                }
+                if topic.trim().is_empty() || topic.contains('/') {
+                    return Err("topic must be a non-empty name without slashes".to_string());
+                }
+                if token.as_deref().is_some_and(|token| token.trim().is_empty()) {
+                    return Err("token is empty".to_string());
+                }
+            }
+            NotificationChannelConfig::Gotify { server_url, token } => {
+                if !is_http_url(server_url) {
+                    return Err("server_url must be an http(s) URL".to_string());
+                }
+                if token.trim().is_empty() {
+                    return Err("token is required".to_string());
+                }
+            }
+            NotificationChannelConfig::Slack { webhook_url } => {
+                if !is_http_url(webhook_url) {
+                    return Err("webhook_url must be an http(s) URL".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+}
+
+fn is_email_address(address: &str) -> bool {
+    address
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.') && !address.contains(char::is_whitespace))
+}
+
+/// A channel must opt into at least one notification type, and only known ones
+pub fn validate_notification_types(types: &[String]) -> Result<(), String> {
+    if types.is_empty() {
+        return Err("notification_types must name at least one type".to_string());
+    }
+    match types.iter().find(|t| !NOTIFICATION_TYPES.contains(&t.as_str())) {
+        Some(unknown) => Err(format!(
+            "Unknown notification type '{}', expected one of {}",
+            unknown,
+            NOTIFICATION_TYPES.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("name must have 1 to {} characters", MAX_NAME_LENGTH));
+    }
+    Ok(())
+}
+
+/// An outbound destination of a user's notifications
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct NotificationChannel {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[schema(value_type = NotificationChannelConfig)]
+    pub config: sqlx::types::Json<NotificationChannelConfig>,
+    /// Notification types sent to the channel
+    pub notification_types: Vec<String>,
+    pub enabled: bool,
+    pub last_sent_at: Option<DateTime<Utc>>,
+    /// Error of the most recent send, cleared when a send succeeds
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NotificationChannel {
+    pub fn accepts(&self, notification_type: &str) -> bool {
+        self.notification_types.iter().any(|t| t == notification_type)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateNotificationChannelRequest {
+    pub name: String,
+    pub config: NotificationChannelConfig,
+    /// Defaults to every notification type
+    pub notification_types: Option<Vec<String>>,
+    /// Defaults to true
+    pub enabled: Option<bool>,
+}
+
+impl CreateNotificationChannelRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name)?;
+        self.config.validate()?;
+        if let Some(types) = &self.notification_types {
+            validate_notification_types(types)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateNotificationChannelRequest {
+    pub name: Option<String>,
+    /// Replaces the whole configuration
+    pub config: Option<NotificationChannelConfig>,
+    pub notification_types: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+impl UpdateNotificationChannelRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        if let Some(config) = &self.config {
+            config.validate()?;
+        }
+        if let Some(types) = &self.notification_types {
+            validate_notification_types(types)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_validation() {
+        let ntfy: NotificationChannelConfig =
+            serde_json::from_value(serde_json::json!({"type": "ntfy", "topic": "readur"})).unwrap();
+        assert!(ntfy.validate().is_ok());
+
+        let nested_topic = NotificationChannelConfig::Ntfy {
+            server_url: None,
+            topic: "a/b".to_string(),
+            token: None,
+        };
+        assert!(nested_topic.validate().is_err());
+
+        let slack = NotificationChannelConfig::Slack { webhook_url: "ftp://hooks.slack.com/x".to_string() };
+        assert!(slack.validate().is_err());
+
+        let email = NotificationChannelConfig::Email { to: Some("not an address".to_string()) };
+        assert!(email.validate().is_err());
+        assert!(NotificationChannelConfig::Email { to: None }.validate().is_ok());
+    }
+
+    #[test]
+    fn test_notification_types_are_opt_in() {
+        assert!(validate_notification_types(&["error".to_string(), "warning".to_string()]).is_ok());
+        assert!(validate_notification_types(&[]).is_err());
+        assert!(validate_notification_types(&["critical".to_string()]).is_err());
+    }
+}
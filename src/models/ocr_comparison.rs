@@ -0,0 +1,408 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Most documents one comparison may cover
+pub const MAX_COMPARISON_DOCUMENTS: usize = 500;
+pub const DEFAULT_COMPARISON_SAMPLE_SIZE: i64 = 50;
+/// Accuracies closer than this count as a tie
+const TIE_MARGIN: f64 = 0.005;
+
+/// An OCR engine and the settings it runs with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "engine", rename_all = "snake_case")]
+pub enum OcrEngineConfig {
+    /// Tesseract with the document owner's OCR settings, except those given here
+    Tesseract {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        language: Option<String>,
+        /// Tesseract `--psm`, 0 to 13
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        page_segmentation_mode: Option<i32>,
+        /// Tesseract `--oem`, 0 to 3
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        engine_mode: Option<i32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        image_preprocessing: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dpi: Option<i32>,
+    },
+    /// The vision model configured for the vision OCR fallback, on up to 10 pages
+    Vision,
+}
+
+impl OcrEngineConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            OcrEngineConfig::Tesseract { page_segmentation_mode, engine_mode, dpi, .. } => {
+                if page_segmentation_mode.is_some_and(|psm| !(0..=13).contains(&psm)) {
+                    return Err("page_segmentation_mode must be between 0 and 13".to_string());
+                }
+                if engine_mode.is_some_and(|oem| !(0..=3).contains(&oem)) {
+                    return Err("engine_mode must be between 0 and 3".to_string());
+                }
+                if dpi.is_some_and(|dpi| !(72..=1200).contains(&dpi)) {
+                    return Err("dpi must be between 72 and 1200".to_string());
+                }
+                Ok(())
+            }
+            OcrEngineConfig::Vision => Ok(()),
+        }
+    }
+}
+
+/// Two engines and the documents to run them on: the listed ones, or a random sample
+/// of those matching the filters
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateOcrComparisonRequest {
+    pub name: String,
+    pub engine_a: OcrEngineConfig,
+    pub engine_b: OcrEngineConfig,
+    pub document_ids: Option<Vec<Uuid>>,
+    pub label_id: Option<Uuid>,
+    pub document_type_id: Option<Uuid>,
+    pub mime_type: Option<String>,
+    /// Documents in a random sample (default: 50)
+    pub sample_size: Option<i64>,
+    /// Reference texts the engines are scored against, by document. Documents whose
+    /// OCR text was corrected by hand are scored against it unless given here.
+    #[serde(default)]
+    pub ground_truth: HashMap<Uuid, String>,
+}
+
+impl CreateOcrComparisonRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.len() > 100 {
+            return Err("name must be 1 to 100 characters".to_string());
+        }
+        self.engine_a.validate().map_err(|e| format!("engine_a: {}", e))?;
+        self.engine_b.validate().map_err(|e| format!("engine_b: {}", e))?;
+        if self.engine_a == self.engine_b {
+            return Err("engine_a and engine_b are the same".to_string());
+        }
+        match &self.document_ids {
+            Some(ids) if ids.is_empty() || ids.len() > MAX_COMPARISON_DOCUMENTS => {
+                Err(format!("document_ids must list 1 to {} documents", MAX_COMPARISON_DOCUMENTS))
+            }
+            Some(_) if self.label_id.is_some() || self.document_type_id.is_some() || self.mime_type.is_some() => {
+                Err("Give either document_ids or filters, not both".to_string())
+            }
+            _ if self.sample_size.is_some_and(|size| !(1..=MAX_COMPARISON_DOCUMENTS as i64).contains(&size)) => {
+                Err(format!("sample_size must be between 1 and {}", MAX_COMPARISON_DOCUMENTS))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn sample_size(&self) -> i64 {
+        self.sample_size.unwrap_or(DEFAULT_COMPARISON_SAMPLE_SIZE)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrComparisonStatus {
+    Queued,
+    /// In progress, or interrupted and resumed at the next start
+    Running,
+    /// Both engines ran on every document; some runs may have failed
+    Completed,
+    /// Stopped by an error that affects every document
+    Failed,
+}
+
+impl OcrComparisonStatus {
+    pub fn is_finished(self) -> bool {
+        !matches!(self, OcrComparisonStatus::Queued | OcrComparisonStatus::Running)
+    }
+}
+
+impl std::fmt::Display for OcrComparisonStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OcrComparisonStatus::Queued => write!(f, "queued"),
+            OcrComparisonStatus::Running => write!(f, "running"),
+            OcrComparisonStatus::Completed => write!(f, "completed"),
+            OcrComparisonStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl TryFrom<String> for OcrComparisonStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "queued" => Ok(OcrComparisonStatus::Queued),
+            "running" => Ok(OcrComparisonStatus::Running),
+            "completed" => Ok(OcrComparisonStatus::Completed),
+            "failed" => Ok(OcrComparisonStatus::Failed),
+            _ => Err(format!("Unknown OCR comparison status: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OcrComparison {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[schema(value_type = OcrEngineConfig)]
+    pub engine_a: sqlx::types::Json<OcrEngineConfig>,
+    #[schema(value_type = OcrEngineConfig)]
+    pub engine_b: sqlx::types::Json<OcrEngineConfig>,
+    #[sqlx(try_from = "String")]
+    pub status: OcrComparisonStatus,
+    pub total_documents: i32,
+    pub processed_documents: i32,
+    /// Why the comparison failed as a whole
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// What both engines made of one document of a comparison. Accuracies are between 0
+/// and 1 and only set for documents with a reference text.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OcrComparisonDocument {
+    pub document_id: Uuid,
+    pub position: i32,
+    /// The document's type, or its MIME type when it has none
+    pub document_class: String,
+    pub ground_truth: Option<String>,
+    pub text_a: Option<String>,
+    pub confidence_a: Option<f32>,
+    pub duration_ms_a: Option<i64>,
+    pub error_a: Option<String>,
+    pub character_accuracy_a: Option<f64>,
+    pub word_accuracy_a: Option<f64>,
+    pub text_b: Option<String>,
+    pub confidence_b: Option<f32>,
+    pub duration_ms_b: Option<i64>,
+    pub error_b: Option<String>,
+    pub character_accuracy_b: Option<f64>,
+    pub word_accuracy_b: Option<f64>,
+    /// Character accuracy of engine B's text against engine A's, without a reference text
+    pub agreement: Option<f64>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+impl OcrComparisonDocument {
+    /// Which engine read the document better: higher character accuracy, then higher
+    /// word accuracy. None without a reference text or when they tie.
+    pub fn winner(&self) -> Option<OcrEngineSide> {
+        let (a, b) = (self.character_accuracy_a?, self.character_accuracy_b?);
+        let (a, b) = if (a - b).abs() > TIE_MARGIN {
+            (a, b)
+        } else {
+            (self.word_accuracy_a.unwrap_or(0.0), self.word_accuracy_b.unwrap_or(0.0))
+        };
+        if (a - b).abs() <= TIE_MARGIN {
+            None
+        } else if a > b {
+            Some(OcrEngineSide::A)
+        } else {
+            Some(OcrEngineSide::B)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrEngineSide {
+    A,
+    B,
+}
+
+/// How one engine did on a class of documents
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct OcrEngineSummary {
+    /// Means over the documents with a reference text
+    pub mean_character_accuracy: Option<f64>,
+    pub mean_word_accuracy: Option<f64>,
+    pub mean_confidence: Option<f64>,
+    pub mean_duration_ms: Option<f64>,
+    /// Documents the engine could not read
+    pub failures: i64,
+}
+
+/// The engines side by side on one class of documents
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct OcrComparisonClassSummary {
+    pub document_class: String,
+    pub documents: i64,
+    /// Documents with a reference text; only these decide the winner
+    pub scored_documents: i64,
+    pub engine_a: OcrEngineSummary,
+    pub engine_b: OcrEngineSummary,
+    pub wins_a: i64,
+    pub wins_b: i64,
+    pub ties: i64,
+    /// The engine that won more scored documents
+    pub winner: Option<OcrEngineSide>,
+    /// Mean agreement of the engines on documents without a reference text
+    pub mean_agreement: Option<f64>,
+}
+
+/// A comparison with its results per document class and over all documents
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OcrComparisonReport {
+    pub comparison: OcrComparison,
+    pub classes: Vec<OcrComparisonClassSummary>,
+    pub overall: OcrComparisonClassSummary,
+}
+
+impl OcrComparisonReport {
+    /// Summarize the processed documents of a comparison, classes in name order
+    pub fn new(comparison: OcrComparison, documents: &[OcrComparisonDocument]) -> Self {
+        let processed: Vec<&OcrComparisonDocument> = documents.iter().filter(|d| d.processed_at.is_some()).collect();
+        let mut by_class: BTreeMap<&str, Vec<&OcrComparisonDocument>> = BTreeMap::new();
+        for document in &processed {
+            by_class.entry(document.document_class.as_str()).or_default().push(document);
+        }
+        let classes = by_class.into_iter().map(|(class, documents)| summarize(class, &documents)).collect();
+        let overall = summarize("all", &processed);
+        Self { comparison, classes, overall }
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// Character accuracy, word accuracy, confidence, duration and failure of one engine
+type EngineResult = (Option<f64>, Option<f64>, Option<f32>, Option<i64>, bool);
+
+impl OcrComparisonDocument {
+    fn result(&self, side: OcrEngineSide) -> EngineResult {
+        match side {
+            OcrEngineSide::A => (
+                self.character_accuracy_a,
+                self.word_accuracy_a,
+                self.confidence_a,
+                self.duration_ms_a,
+                self.error_a.is_some(),
+            ),
+            OcrEngineSide::B => (
+                self.character_accuracy_b,
+                self.word_accuracy_b,
+                self.confidence_b,
+                self.duration_ms_b,
+                self.error_b.is_some(),
+            ),
+        }
+    }
+}
+
+fn summarize_engine(documents: &[&OcrComparisonDocument], side: OcrEngineSide) -> OcrEngineSummary {
+    let results: Vec<(bool, EngineResult)> =
+        documents.iter().map(|d| (d.ground_truth.is_some(), d.result(side))).collect();
+    let scored = || results.iter().filter(|(scored, _)| *scored).map(|(_, result)| result);
+    OcrEngineSummary {
+        mean_character_accuracy: mean(scored().filter_map(|result| result.0)),
+        mean_word_accuracy: mean(scored().filter_map(|result| result.1)),
+        mean_confidence: mean(results.iter().filter_map(|(_, result)| result.2).map(f64::from)),
+        mean_duration_ms: mean(results.iter().filter_map(|(_, result)| result.3).map(|ms| ms as f64)),
+        failures: results.iter().filter(|(_, result)| result.4).count() as i64,
+    }
+}
+
+fn summarize(class: &str, documents: &[&OcrComparisonDocument]) -> OcrComparisonClassSummary {
+    let scored: Vec<&&OcrComparisonDocument> = documents.iter().filter(|d| d.ground_truth.is_some()).collect();
+    let mut summary = OcrComparisonClassSummary {
+        document_class: class.to_string(),
+        documents: documents.len() as i64,
+        scored_documents: scored.len() as i64,
+        engine_a: summarize_engine(documents, OcrEngineSide::A),
+        engine_b: summarize_engine(documents, OcrEngineSide::B),
+        mean_agreement: mean(documents.iter().filter_map(|d| d.agreement)),
+        ..Default::default()
+    };
+    for document in &scored {
+        match document.winner() {
+            Some(OcrEngineSide::A) => summary.wins_a += 1,
+            Some(OcrEngineSide::B) => summary.wins_b += 1,
+            None => summary.ties += 1,
+        }
+    }
+    summary.winner = match summary.wins_a.cmp(&summary.wins_b) {
+        std::cmp::Ordering::Greater => Some(OcrEngineSide::A),
+        std::cmp::Ordering::Less => Some(OcrEngineSide::B),
+        std::cmp::Ordering::Equal => None,
+    };
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scored(class: &str, a: f64, b: f64) -> OcrComparisonDocument {
+        OcrComparisonDocument {
+            document_id: Uuid::new_v4(),
+            document_class: class.to_string(),
+            ground_truth: Some("reference".to_string()),
+            character_accuracy_a: Some(a),
+            word_accuracy_a: Some(a),
+            character_accuracy_b: Some(b),
+            word_accuracy_b: Some(b),
+            processed_at: Some(Utc::now()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_engine_config_serialization() {
+        let engine: OcrEngineConfig =
+            serde_json::from_value(serde_json::json!({"engine": "tesseract", "page_segmentation_mode": 6})).unwrap();
+        assert!(matches!(engine, OcrEngineConfig::Tesseract { page_segmentation_mode: Some(6), .. }));
+        assert_eq!(serde_json::to_value(OcrEngineConfig::Vision).unwrap(), serde_json::json!({"engine": "vision"}));
+        let invalid: OcrEngineConfig = serde_json::from_value(serde_json::json!({"engine": "tesseract", "engine_mode": 7})).unwrap();
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_winner_per_class() {
+        let mut unscored = scored("Receipt", 0.0, 0.0);
+        unscored.ground_truth = None;
+        unscored.character_accuracy_a = None;
+        unscored.character_accuracy_b = None;
+        unscored.agreement = Some(0.8);
+        let documents = vec![
+            scored("Invoice", 0.95, 0.90),
+            scored("Invoice", 0.97, 0.91),
+            scored("Invoice", 0.80, 0.80),
+            scored("Receipt", 0.60, 0.85),
+            unscored,
+        ];
+
+        let comparison = OcrComparison {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: "psm 3 vs 6".to_string(),
+            engine_a: sqlx::types::Json(OcrEngineConfig::Vision),
+            engine_b: sqlx::types::Json(OcrEngineConfig::Vision),
+            status: OcrComparisonStatus::Completed,
+            total_documents: 5,
+            processed_documents: 5,
+            error: None,
+            created_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+        };
+        let report = OcrComparisonReport::new(comparison, &documents);
+        let invoice = &report.classes[0];
+        assert_eq!((invoice.document_class.as_str(), invoice.wins_a, invoice.wins_b, invoice.ties), ("Invoice", 2, 0, 1));
+        assert_eq!(invoice.winner, Some(OcrEngineSide::A));
+        let receipt = &report.classes[1];
+        assert_eq!((receipt.documents, receipt.scored_documents, receipt.winner), (2, 1, Some(OcrEngineSide::B)));
+        assert_eq!(receipt.mean_agreement, Some(0.8));
+        assert_eq!((report.overall.wins_a, report.overall.wins_b), (2, 1));
+    }
+}
@@ -0,0 +1,118 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::ocr::PAGE_BREAK;
+
+/// New text for one page of the OCR text
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct OcrPageText {
+    /// 1-based page number
+    pub page: u32,
+    pub text: String,
+}
+
+/// A correction of a document's OCR text: either the whole text or some of its pages
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateOcrTextRequest {
+    /// Replaces the whole text
+    pub text: Option<String>,
+    /// Replace these pages and keep the others
+    pub pages: Option<Vec<OcrPageText>>,
+    /// Extract the knowledge graph again from the corrected text
+    #[serde(default)]
+    pub reanalyze: bool,
+}
+
+impl UpdateOcrTextRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.text, &self.pages) {
+            (Some(_), Some(_)) => Err("Give either text or pages, not both".to_string()),
+            (None, None) => Err("Give the corrected text or pages".to_string()),
+            (None, Some(pages)) if pages.is_empty() => Err("No pages to correct".to_string()),
+            (None, Some(pages)) => {
+                for (i, edit) in pages.iter().enumerate() {
+                    if edit.page == 0 {
+                        return Err("Pages are numbered from 1".to_string());
+                    }
+                    if edit.text.contains(PAGE_BREAK) {
+                        return Err(format!("The text of page {} contains a page break", edit.page));
+                    }
+                    if pages[..i].iter().any(|other| other.page == edit.page) {
+                        return Err(format!("Page {} is given twice", edit.page));
+                    }
+                }
+                Ok(())
+            }
+            (Some(_), None) => Ok(()),
+        }
+    }
+
+    /// The corrected text of a document whose text is `current`
+    pub fn apply(&self, current: Option<&str>) -> Result<String, String> {
+        if let Some(text) = &self.text {
+            return Ok(text.clone());
+        }
+        let current = current.unwrap_or_default();
+        let mut pages: Vec<&str> = current.split(PAGE_BREAK).collect();
+        // A final page break ends the last page rather than starting an empty one
+        let terminated = pages.len() > 1 && pages.last() == Some(&"");
+        if terminated {
+            pages.pop();
+        }
+
+        let edits = self.pages.as_deref().unwrap_or_default();
+        for edit in edits {
+            let index = edit.page as usize - 1;
+            let page = pages
+                .get_mut(index)
+                .ok_or_else(|| format!("The document's text has no page {}", edit.page))?;
+            *page = edit.text.as_str();
+        }
+
+        let mut text = pages.join(&PAGE_BREAK.to_string());
+        if terminated {
+            text.push(PAGE_BREAK);
+        }
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(edits: &[(u32, &str)]) -> UpdateOcrTextRequest {
+        UpdateOcrTextRequest {
+            text: None,
+            pages: Some(edits.iter().map(|(page, text)| OcrPageText { page: *page, text: text.to_string() }).collect()),
+            reanalyze: false,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let both = UpdateOcrTextRequest { text: Some("a".to_string()), ..pages(&[(1, "b")]) };
+        assert!(both.validate().is_err());
+        assert!(pages(&[]).validate().is_err());
+        assert!(pages(&[(0, "a")]).validate().is_err());
+        assert!(pages(&[(1, "a\u{c}b")]).validate().is_err());
+        assert!(pages(&[(2, "a"), (2, "b")]).validate().is_err());
+        assert!(pages(&[(1, "a"), (2, "b")]).validate().is_ok());
+    }
+
+    #[test]
+    fn test_apply_pages() {
+        let text = "first pgae\u{c}second page\u{c}third\u{c}";
+        assert_eq!(pages(&[(1, "first page")]).apply(Some(text)).unwrap(), "first page\u{c}second page\u{c}third\u{c}");
+        assert_eq!(pages(&[(3, "3rd")]).apply(Some(text)).unwrap(), "first pgae\u{c}second page\u{c}3rd\u{c}");
+        assert!(pages(&[(4, "none")]).apply(Some(text)).is_err());
+        assert_eq!(pages(&[(1, "only")]).apply(Some("single")).unwrap(), "only");
+        assert_eq!(pages(&[(1, "new")]).apply(None).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_apply_full_text() {
+        let request = UpdateOcrTextRequest { text: Some("whole".to_string()), pages: None, reanalyze: false };
+        assert_eq!(request.apply(Some("a\u{c}b")).unwrap(), "whole");
+    }
+}
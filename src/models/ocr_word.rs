@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use super::BoundingBox;
+
+/// Words with a confidence below this (0-100) are flagged unless configured otherwise
+pub const DEFAULT_LOW_CONFIDENCE_THRESHOLD: f32 = 60.0;
+
+/// A word Tesseract recognized, with how sure it was of it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OcrWordConfidence {
+    pub text: String,
+    /// 0 to 100
+    pub confidence: f32,
+    pub bbox: BoundingBox,
+}
+
+/// The words of one page of a document, boxes in pixels of the page as rendered
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OcrPageWords {
+    pub document_id: Uuid,
+    pub page_number: i32,
+    pub page_width: i32,
+    pub page_height: i32,
+    #[schema(value_type = Vec<OcrWordConfidence>)]
+    pub words: sqlx::types::Json<Vec<OcrWordConfidence>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateOcrPageWords {
+    pub page_number: i32,
+    pub page_width: u32,
+    pub page_height: u32,
+    pub words: Vec<OcrWordConfidence>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct LowConfidenceWordsQuery {
+    /// Flag words below this confidence, 0 to 100 (default: the server's threshold)
+    pub threshold: Option<f32>,
+}
+
+/// A word to check, with where it is so it can be marked on the page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LowConfidenceWord {
+    pub page_number: i32,
+    /// Position of the word on its page in reading order, from 0
+    pub word_index: usize,
+    pub text: String,
+    pub confidence: f32,
+    pub bbox: BoundingBox,
+    pub page_width: i32,
+    pub page_height: i32,
+}
+
+/// A document's uncertain words, least certain first
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LowConfidenceWordsResponse {
+    pub document_id: Uuid,
+    pub threshold: f32,
+    /// Pages whose words were analyzed; none until word analysis has run
+    pub pages_analyzed: usize,
+    pub total_words: usize,
+    pub words: Vec<LowConfidenceWord>,
+}
+
+impl LowConfidenceWordsResponse {
+    pub fn new(document_id: Uuid, threshold: f32, pages: &[OcrPageWords]) -> Self {
+        let mut words: Vec<LowConfidenceWord> = pages
+            .iter()
+            .flat_map(|page| {
+                page.words.iter().enumerate().filter(move |(_, word)| word.confidence < threshold).map(
+                    move |(word_index, word)| LowConfidenceWord {
+                        page_number: page.page_number,
+                        word_index,
+                        text: word.text.clone(),
+                        confidence: word.confidence,
+                        bbox: word.bbox,
+                        page_width: page.page_width,
+                        page_height: page.page_height,
+                    },
+                )
+            })
+            .collect();
+        words.sort_by(|a, b| {
+            a.confidence
+                .total_cmp(&b.confidence)
+                .then(a.page_number.cmp(&b.page_number))
+                .then(a.word_index.cmp(&b.word_index))
+        });
+
+        Self {
+            document_id,
+            threshold,
+            pages_analyzed: pages.len(),
+            total_words: pages.iter().map(|page| page.words.len()).sum(),
+            words,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct LowConfidenceDocumentsQuery {
+    /// Flag words below this confidence, 0 to 100 (default: the server's threshold)
+    pub threshold: Option<f32>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A document in the correction queue
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LowConfidenceDocument {
+    pub document_id: Uuid,
+    pub filename: String,
+    pub flagged_words: i64,
+    pub total_words: i64,
+    pub lowest_confidence: Option<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, confidence: f32) -> OcrWordConfidence {
+        OcrWordConfidence {
+            text: text.to_string(),
+            confidence,
+            bbox: BoundingBox { x: 0, y: 0, width: 10, height: 10 },
+        }
+    }
+
+    fn page(page_number: i32, words: Vec<OcrWordConfidence>) -> OcrPageWords {
+        OcrPageWords {
+            document_id: Uuid::nil(),
+            page_number,
+            page_width: 2480,
+            page_height: 3508,
+            words: sqlx::types::Json(words),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_low_confidence_words_least_certain_first() {
+        let pages = vec![
+            page(1, vec![word("Invoice", 96.0), word("Tota1", 41.5), word("EUR", 88.0)]),
+            page(2, vec![word("Pa1d", 22.0), word("thanks", 59.9)]),
+        ];
+        let response = LowConfidenceWordsResponse::new(Uuid::nil(), 60.0, &pages);
+        assert_eq!((response.pages_analyzed, response.total_words), (2, 5));
+        let flagged: Vec<(&str, i32, usize)> =
+            response.words.iter().map(|w| (w.text.as_str(), w.page_number, w.word_index)).collect();
+        assert_eq!(flagged, vec![("Pa1d", 2, 0), ("Tota1", 1, 1), ("thanks", 2, 1)]);
+
+        assert!(LowConfidenceWordsResponse::new(Uuid::nil(), 20.0, &pages).words.is_empty());
+    }
+}
@@ -0,0 +1,450 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::FromRow;
+use std::collections::HashSet;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Most regions a template may define
+pub const MAX_OMR_REGIONS: usize = 200;
+const MAX_OMR_MATCH_PHRASES: usize = 20;
+const MAX_OMR_NAME_LENGTH: usize = 200;
+const MAX_OMR_KEY_LENGTH: usize = 100;
+/// Checkboxes count as marked from this share of dark pixels when a template does not say
+pub const DEFAULT_OMR_FILL_THRESHOLD: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OmrRegionKind {
+    /// A box that is marked or not
+    Checkbox,
+    /// A field filled in by hand or typewriter, read with OCR
+    Text,
+}
+
+/// A checkbox or field of a form template, as fractions (0-1) of the page's width
+/// and height measured from the top-left corner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OmrRegion {
+    /// Key of the field in the extracted data, unique within the template
+    pub key: String,
+    pub label: Option<String>,
+    pub kind: OmrRegionKind,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Checkboxes sharing a group are the options of one field, keyed by the group,
+    /// whose value is the marked option(s)
+    pub group: Option<String>,
+    /// What a grouped checkbox stands for when marked; defaults to its label, then its key
+    pub value: Option<String>,
+}
+
+impl OmrRegion {
+    fn is_within_page(&self) -> bool {
+        [self.x, self.y, self.width, self.height].iter().all(|v| v.is_finite())
+            && self.x >= 0.0
+            && self.y >= 0.0
+            && self.width > 0.0
+            && self.height > 0.0
+            && self.x + self.width <= 1.0 + 1e-6
+            && self.y + self.height <= 1.0 + 1e-6
+    }
+
+    /// The group's value when this checkbox is marked
+    pub fn option_value(&self) -> String {
+        self.value
+            .clone()
+            .or_else(|| self.label.clone())
+            .unwrap_or_else(|| self.key.clone())
+    }
+}
+
+/// A form layout defined by an admin, with the regions of its checkboxes and fields
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OmrTemplate {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// 1-based page of the scanned form the regions are on
+    pub page: i32,
+    /// Phrases identifying the form in OCR text; a document matches when it contains
+    /// all of them. Templates without phrases are only applied on request.
+    pub match_phrases: Vec<String>,
+    #[schema(value_type = Vec<OmrRegion>)]
+    pub regions: sqlx::types::Json<Vec<OmrRegion>>,
+    /// Share of dark pixels inside a checkbox from which it counts as marked
+    pub fill_threshold: f32,
+    pub enabled: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Lowercase with single spaces, so line breaks and OCR spacing do not matter
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+impl OmrTemplate {
+    /// How many phrases identify the form in the text, when it contains all of them;
+    /// None when it does not match or the template has no phrases
+    pub fn match_score(&self, text: &str) -> Option<usize> {
+        let phrases: Vec<String> = self
+            .match_phrases
+            .iter()
+            .map(|phrase| normalize_text(phrase))
+            .filter(|phrase| !phrase.is_empty())
+            .collect();
+        if phrases.is_empty() {
+            return None;
+        }
+        let text = normalize_text(text);
+        phrases.iter().all(|phrase| text.contains(phrase.as_str())).then_some(phrases.len())
+    }
+
+    /// Keys of the fields read with OCR
+    pub fn text_keys(&self) -> Vec<String> {
+        self.regions
+            .iter()
+            .filter(|region| region.kind == OmrRegionKind::Text)
+            .map(|region| region.key.clone())
+            .collect()
+    }
+}
+
+/// The template among `templates` that identifies the text best: the matching one
+/// with the most phrases
+pub fn best_omr_template<'a>(templates: &'a [OmrTemplate], text: &str) -> Option<&'a OmrTemplate> {
+    templates
+        .iter()
+        .filter(|template| template.enabled)
+        .filter_map(|template| template.match_score(text).map(|score| (template, score)))
+        .max_by_key(|(_, score)| *score)
+        .map(|(template, _)| template)
+}
+
+fn validate_regions(regions: &[OmrRegion]) -> Result<(), String> {
+    if regions.is_empty() {
+        return Err("A template needs at least one region".to_string());
+    }
+    if regions.len() > MAX_OMR_REGIONS {
+        return Err(format!("Templates are limited to {} regions", MAX_OMR_REGIONS));
+    }
+
+    let mut keys = HashSet::new();
+    for region in regions {
+        let key = region.key.trim();
+        if key.is_empty() || key.chars().count() > MAX_OMR_KEY_LENGTH {
+            return Err(format!("Region keys must have 1 to {} characters", MAX_OMR_KEY_LENGTH));
+        }
+        if !keys.insert(key) {
+            return Err(format!("Region key '{}' is used twice", key));
+        }
+        if !region.is_within_page() {
+            return Err(format!("Region '{}' is not within the page", key));
+        }
+        if region.group.is_some() && region.kind != OmrRegionKind::Checkbox {
+            return Err(format!("Region '{}' is grouped but not a checkbox", key));
+        }
+    }
+
+    // A group becomes a field of its own, so it cannot share a key with a region
+    let groups: HashSet<&str> = regions.iter().filter_map(|region| region.group.as_deref()).map(str::trim).collect();
+    if let Some(group) = groups.iter().find(|group| group.is_empty() || keys.contains(*group)) {
+        return Err(format!("Group '{}' is empty or also a region key", group));
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_OMR_NAME_LENGTH {
+        return Err(format!("Template names must have 1 to {} characters", MAX_OMR_NAME_LENGTH));
+    }
+    Ok(())
+}
+
+fn validate_settings(page: Option<i32>, match_phrases: Option<&[String]>, fill_threshold: Option<f32>) -> Result<(), String> {
+    if page.is_some_and(|page| page < 1) {
+        return Err("Pages are numbered from 1".to_string());
+    }
+    if match_phrases.is_some_and(|phrases| phrases.len() > MAX_OMR_MATCH_PHRASES) {
+        return Err(format!("Templates are limited to {} match phrases", MAX_OMR_MATCH_PHRASES));
+    }
+    if fill_threshold.is_some_and(|threshold| !(threshold > 0.0 && threshold < 1.0)) {
+        return Err("The fill threshold must be between 0 and 1".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateOmrTemplateRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// Default 1
+    pub page: Option<i32>,
+    #[serde(default)]
+    pub match_phrases: Vec<String>,
+    pub regions: Vec<OmrRegion>,
+    /// Default 0.15
+    pub fill_threshold: Option<f32>,
+    /// Default true
+    pub enabled: Option<bool>,
+}
+
+impl CreateOmrTemplateRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name)?;
+        validate_settings(self.page, Some(&self.match_phrases), self.fill_threshold)?;
+        validate_regions(&self.regions)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateOmrTemplateRequest {
+    pub name: Option<String>,
+    /// An empty description removes it
+    pub description: Option<String>,
+    pub page: Option<i32>,
+    pub match_phrases: Option<Vec<String>>,
+    /// Replaces all regions; results extracted before keep their data
+    pub regions: Option<Vec<OmrRegion>>,
+    pub fill_threshold: Option<f32>,
+    pub enabled: Option<bool>,
+}
+
+impl UpdateOmrTemplateRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        validate_settings(self.page, self.match_phrases.as_deref(), self.fill_threshold)?;
+        match &self.regions {
+            Some(regions) => validate_regions(regions),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What was read in one region of a scanned form
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OmrRegionReading {
+    pub key: String,
+    pub kind: OmrRegionKind,
+    /// Share of dark pixels inside a checkbox
+    pub fill_ratio: Option<f32>,
+    pub marked: Option<bool>,
+    /// Text of a field; None when it could not be read
+    pub text: Option<String>,
+}
+
+/// The marks and fields read from a document with a template
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OmrResult {
+    pub document_id: Uuid,
+    pub template_id: Uuid,
+    /// Field key to value: true or false for checkboxes, the marked option of a group
+    /// (an array when several are marked, null when none is), the text of a field
+    #[schema(value_type = Object)]
+    pub data: sqlx::types::Json<Map<String, Value>>,
+    #[schema(value_type = Vec<OmrRegionReading>)]
+    pub readings: sqlx::types::Json<Vec<OmrRegionReading>>,
+    pub extracted_at: DateTime<Utc>,
+}
+
+/// A result as listed for its template, with the document's name
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OmrResultListItem {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub result: OmrResult,
+    pub filename: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct OmrResultsQuery {
+    /// Only results whose field `field` has `value`
+    pub field: Option<String>,
+    /// `true`, `false` and `null` match checkboxes and empty fields; anything else a
+    /// group option or text. Matches a group with several options marked when one of
+    /// them is this value.
+    pub value: Option<String>,
+    /// Default 50, at most 500
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl OmrResultsQuery {
+    /// The field and JSON value to filter on, when both are given
+    pub fn filter(&self) -> Option<(String, Value)> {
+        let field = self.field.as_deref().map(str::trim).filter(|field| !field.is_empty())?;
+        let value = match self.value.as_deref()? {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "null" => Value::Null,
+            other => Value::String(other.to_string()),
+        };
+        Some((field.to_string(), value))
+    }
+}
+
+/// How many results of a template have a value in a checkbox or group field
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OmrFieldCount {
+    pub field: String,
+    #[schema(value_type = Object)]
+    pub value: sqlx::types::Json<Value>,
+    pub count: i64,
+}
+
+/// Read a document with a template. Without `template_id` the template is found by
+/// its match phrases.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct OmrExtractRequest {
+    pub template_id: Option<Uuid>,
+}
+
+/// The extracted data of a template's regions: ungrouped checkboxes as true or false,
+/// groups as their marked option(s) and fields as their text. `readings` follow the
+/// template's regions by key; regions without a reading are left out.
+pub fn omr_data(regions: &[OmrRegion], readings: &[OmrRegionReading]) -> Map<String, Value> {
+    let reading = |key: &str| readings.iter().find(|reading| reading.key == key);
+    let mut data = Map::new();
+
+    for region in regions {
+        let Some(reading) = reading(&region.key) else {
+            continue;
+        };
+        match (&region.kind, &region.group) {
+            (OmrRegionKind::Text, _) => {
+                let text = reading.text.as_deref().map(str::trim).filter(|text| !text.is_empty());
+                data.insert(region.key.clone(), text.map(|t| Value::String(t.to_string())).unwrap_or(Value::Null));
+            }
+            (OmrRegionKind::Checkbox, None) => {
+                data.insert(region.key.clone(), Value::Bool(reading.marked.unwrap_or(false)));
+            }
+            (OmrRegionKind::Checkbox, Some(group)) => {
+                let entry = data.entry(group.trim().to_string()).or_insert(Value::Null);
+                if !reading.marked.unwrap_or(false) {
+                    continue;
+                }
+                let option = Value::String(region.option_value());
+                *entry = match entry.take() {
+                    Value::Null => option,
+                    Value::Array(mut options) => {
+                        options.push(option);
+                        Value::Array(options)
+                    }
+                    first => Value::Array(vec![first, option]),
+                };
+            }
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkbox(key: &str, group: Option<&str>) -> OmrRegion {
+        OmrRegion {
+            key: key.to_string(),
+            label: None,
+            kind: OmrRegionKind::Checkbox,
+            x: 0.1,
+            y: 0.1,
+            width: 0.02,
+            height: 0.02,
+            group: group.map(str::to_string),
+            value: None,
+        }
+    }
+
+    fn template(phrases: &[&str]) -> OmrTemplate {
+        OmrTemplate {
+            id: Uuid::new_v4(),
+            name: "Consent form".to_string(),
+            description: None,
+            page: 1,
+            match_phrases: phrases.iter().map(|p| p.to_string()).collect(),
+            regions: sqlx::types::Json(vec![checkbox("consent", None)]),
+            fill_threshold: DEFAULT_OMR_FILL_THRESHOLD,
+            enabled: true,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_region_validation() {
+        assert!(validate_regions(&[checkbox("a", Some("color")), checkbox("b", Some("color"))]).is_ok());
+        assert!(validate_regions(&[]).is_err());
+        assert!(validate_regions(&[checkbox("a", None), checkbox("a", None)]).is_err());
+        assert!(validate_regions(&[checkbox("a", Some("a"))]).is_err());
+        assert!(validate_regions(&[OmrRegion { x: 0.99, ..checkbox("a", None) }]).is_err());
+        let grouped_text = OmrRegion { kind: OmrRegionKind::Text, ..checkbox("name", Some("g")) };
+        assert!(validate_regions(&[grouped_text]).is_err());
+    }
+
+    #[test]
+    fn test_template_matching() {
+        let text = "PATIENT CONSENT\nForm   HC-12\nI agree to the treatment";
+        let generic = template(&["consent"]);
+        let specific = template(&["Patient consent", "form hc-12"]);
+        let other = template(&["Tax return"]);
+        assert_eq!(specific.match_score(text), Some(2));
+        assert_eq!(other.match_score(text), None);
+        assert_eq!(template(&[]).match_score(text), None);
+
+        let templates = vec![generic, specific.clone(), other];
+        assert_eq!(best_omr_template(&templates, text).map(|t| t.id), Some(specific.id));
+    }
+
+    #[test]
+    fn test_omr_data() {
+        let regions = vec![
+            checkbox("consent", None),
+            OmrRegion { value: Some("red".to_string()), ..checkbox("color_red", Some("color")) },
+            OmrRegion { label: Some("Blue".to_string()), ..checkbox("color_blue", Some("color")) },
+            checkbox("size_s", Some("size")),
+            OmrRegion { kind: OmrRegionKind::Text, ..checkbox("name", None) },
+        ];
+        let read = |key: &str, marked: bool| OmrRegionReading {
+            key: key.to_string(),
+            kind: OmrRegionKind::Checkbox,
+            fill_ratio: Some(if marked { 0.4 } else { 0.01 }),
+            marked: Some(marked),
+            text: None,
+        };
+        let readings = vec![
+            read("consent", true),
+            read("color_red", true),
+            read("color_blue", true),
+            read("size_s", false),
+            OmrRegionReading { kind: OmrRegionKind::Text, text: Some(" Jane Doe\n".to_string()), ..read("name", false) },
+        ];
+
+        let data = omr_data(&regions, &readings);
+        assert_eq!(data["consent"], Value::Bool(true));
+        assert_eq!(data["color"], serde_json::json!(["red", "Blue"]));
+        assert_eq!(data["size"], Value::Null);
+        assert_eq!(data["name"], Value::String("Jane Doe".to_string()));
+    }
+
+    #[test]
+    fn test_results_filter() {
+        let query = |field: &str, value: &str| OmrResultsQuery {
+            field: Some(field.to_string()),
+            value: Some(value.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query("consent", "true").filter(), Some(("consent".to_string(), Value::Bool(true))));
+        assert_eq!(query("color", "red").filter(), Some(("color".to_string(), Value::String("red".to_string()))));
+        assert_eq!(query(" ", "red").filter(), None);
+    }
+}
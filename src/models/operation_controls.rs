@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+const MAX_MAINTENANCE_MESSAGE_LENGTH: usize = 1000;
+
+/// Shown to clients whose writes are rejected when the admin gave no message
+pub const DEFAULT_MAINTENANCE_MESSAGE: &str =
+    "Readur is in maintenance mode and accepts no changes for now. Documents can still be viewed and searched.";
+
+/// A background queue an admin can pause
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueKind {
+    /// OCR of new and requeued documents
+    Ocr,
+    /// Scheduled and manual syncs of sources
+    Sync,
+    /// Knowledge-graph analysis, single documents and batches
+    Analysis,
+}
+
+impl QueueKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ocr" => Some(QueueKind::Ocr),
+            "sync" => Some(QueueKind::Sync),
+            "analysis" => Some(QueueKind::Analysis),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QueueKind::Ocr => "ocr",
+            QueueKind::Sync => "sync",
+            QueueKind::Analysis => "analysis",
+        }
+    }
+}
+
+/// Paused queues and the maintenance mode, the same on every node
+#[derive(Debug, Clone, Default, Serialize, FromRow, ToSchema)]
+pub struct OperationControls {
+    pub ocr_paused: bool,
+    pub sync_paused: bool,
+    pub analysis_paused: bool,
+    /// Writes are rejected and every queue is held while it is on
+    pub maintenance_mode: bool,
+    pub maintenance_message: Option<String>,
+    pub maintenance_started_at: Option<DateTime<Utc>>,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl OperationControls {
+    /// Whether a queue takes no work, because it is paused or for maintenance
+    pub fn holds(&self, queue: QueueKind) -> bool {
+        self.maintenance_mode || self.paused(queue)
+    }
+
+    /// Whether an admin paused the queue itself
+    pub fn paused(&self, queue: QueueKind) -> bool {
+        match queue {
+            QueueKind::Ocr => self.ocr_paused,
+            QueueKind::Sync => self.sync_paused,
+            QueueKind::Analysis => self.analysis_paused,
+        }
+    }
+
+    /// Message for clients whose writes are rejected
+    pub fn message(&self) -> &str {
+        self.maintenance_message.as_deref().unwrap_or(DEFAULT_MAINTENANCE_MESSAGE)
+    }
+}
+
+/// State of one queue
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QueueControlStatus {
+    pub queue: QueueKind,
+    pub paused: bool,
+    /// Held by the maintenance mode, whether paused or not
+    pub held_for_maintenance: bool,
+}
+
+/// Turn the maintenance mode on or off
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateMaintenanceRequest {
+    pub enabled: bool,
+    /// Shown to clients whose writes are rejected, instead of the built-in message
+    pub message: Option<String>,
+}
+
+impl UpdateMaintenanceRequest {
+    /// The trimmed message, empty ones dropped
+    pub fn message(&self) -> Result<Option<String>, String> {
+        let message = self.message.as_deref().map(str::trim).filter(|m| !m.is_empty());
+        if message.is_some_and(|m| m.chars().count() > MAX_MAINTENANCE_MESSAGE_LENGTH) {
+            return Err(format!("Message must be at most {} characters", MAX_MAINTENANCE_MESSAGE_LENGTH));
+        }
+        Ok(message.map(str::to_string))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maintenance_holds_every_queue() {
+        let controls = OperationControls { sync_paused: true, ..Default::default() };
+        assert!(controls.holds(QueueKind::Sync));
+        assert!(!controls.holds(QueueKind::Ocr));
+
+        let controls = OperationControls { maintenance_mode: true, ..Default::default() };
+        assert!(controls.holds(QueueKind::Ocr) && controls.holds(QueueKind::Analysis));
+        assert!(!controls.paused(QueueKind::Ocr));
+        assert_eq!(controls.message(), DEFAULT_MAINTENANCE_MESSAGE);
+    }
+
+    #[test]
+    fn test_maintenance_message() {
+        let request = |message: &str| UpdateMaintenanceRequest { enabled: true, message: Some(message.to_string()) };
+        assert_eq!(request("  Moving storage  ").message(), Ok(Some("Moving storage".to_string())));
+        assert_eq!(request("   ").message(), Ok(None));
+        assert!(request(&"x".repeat(MAX_MAINTENANCE_MESSAGE_LENGTH + 1)).message().is_err());
+    }
+}
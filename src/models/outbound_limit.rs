@@ -0,0 +1,180 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+const MAX_OUTBOUND_CONCURRENCY: i32 = 1_000;
+const MAX_OUTBOUND_REQUESTS_PER_MINUTE: i32 = 100_000;
+
+/// What an outbound limit applies to: every request readur sends, or those to one
+/// kind of service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutboundScope {
+    Global,
+    /// The chat and vision model
+    Llm,
+    /// WebDAV sources such as Nextcloud
+    Webdav,
+    #[serde(rename = "onedrive")]
+    OneDrive,
+    Dropbox,
+}
+
+impl OutboundScope {
+    pub const ALL: [OutboundScope; 5] = [
+        OutboundScope::Global,
+        OutboundScope::Llm,
+        OutboundScope::Webdav,
+        OutboundScope::OneDrive,
+        OutboundScope::Dropbox,
+    ];
+
+    /// Prefix of the environment variables setting the scope's default limits
+    pub fn env_prefix(self) -> &'static str {
+        match self {
+            OutboundScope::Global => "OUTBOUND",
+            OutboundScope::Llm => "OUTBOUND_LLM",
+            OutboundScope::Webdav => "OUTBOUND_WEBDAV",
+            OutboundScope::OneDrive => "OUTBOUND_ONEDRIVE",
+            OutboundScope::Dropbox => "OUTBOUND_DROPBOX",
+        }
+    }
+}
+
+impl std::fmt::Display for OutboundScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutboundScope::Global => write!(f, "global"),
+            OutboundScope::Llm => write!(f, "llm"),
+            OutboundScope::Webdav => write!(f, "webdav"),
+            OutboundScope::OneDrive => write!(f, "onedrive"),
+            OutboundScope::Dropbox => write!(f, "dropbox"),
+        }
+    }
+}
+
+impl TryFrom<String> for OutboundScope {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        OutboundScope::ALL
+            .into_iter()
+            .find(|scope| scope.to_string() == value)
+            .ok_or_else(|| format!("Unknown outbound scope: {}", value))
+    }
+}
+
+/// Concurrency and rate limits of a scope; a limit that is not set does not apply
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct OutboundLimit {
+    /// Requests in flight at once
+    pub max_concurrent: Option<i32>,
+    /// Requests started per minute on average
+    pub requests_per_minute: Option<i32>,
+    /// Requests that may start at once after a quiet period; defaults to
+    /// `requests_per_minute`
+    pub burst: Option<i32>,
+}
+
+impl OutboundLimit {
+    /// Limits from `<prefix>_MAX_CONCURRENT`, `<prefix>_REQUESTS_PER_MINUTE` and
+    /// `<prefix>_BURST`; invalid values are left unset
+    pub fn from_lookup(prefix: &str, lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let number = |suffix: &str| {
+            let name = format!("{}_{}", prefix, suffix);
+            lookup(&name).and_then(|value| match value.trim().parse::<i32>() {
+                Ok(n) if n > 0 => Some(n),
+                _ => {
+                    tracing::warn!("Ignoring invalid {}: '{}'", name, value);
+                    None
+                }
+            })
+        };
+        OutboundLimit {
+            max_concurrent: number("MAX_CONCURRENT"),
+            requests_per_minute: number("REQUESTS_PER_MINUTE"),
+            burst: number("BURST"),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_concurrent.is_some_and(|n| !(1..=MAX_OUTBOUND_CONCURRENCY).contains(&n)) {
+            return Err(format!("max_concurrent must be between 1 and {}", MAX_OUTBOUND_CONCURRENCY));
+        }
+        if self.requests_per_minute.is_some_and(|n| !(1..=MAX_OUTBOUND_REQUESTS_PER_MINUTE).contains(&n)) {
+            return Err(format!("requests_per_minute must be between 1 and {}", MAX_OUTBOUND_REQUESTS_PER_MINUTE));
+        }
+        match (self.burst, self.requests_per_minute) {
+            (Some(_), None) => Err("burst needs requests_per_minute".to_string()),
+            (Some(burst), Some(_)) if !(1..=MAX_OUTBOUND_REQUESTS_PER_MINUTE).contains(&burst) => {
+                Err(format!("burst must be between 1 and {}", MAX_OUTBOUND_REQUESTS_PER_MINUTE))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Limits an admin set for a scope, replacing those from the environment
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OutboundLimitOverride {
+    #[sqlx(try_from = "String")]
+    pub scope: OutboundScope,
+    pub max_concurrent: Option<i32>,
+    pub requests_per_minute: Option<i32>,
+    pub burst: Option<i32>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl OutboundLimitOverride {
+    pub fn limit(&self) -> OutboundLimit {
+        OutboundLimit {
+            max_concurrent: self.max_concurrent,
+            requests_per_minute: self.requests_per_minute,
+            burst: self.burst,
+        }
+    }
+}
+
+/// The limits a scope runs with and its current load
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OutboundLimitStatus {
+    pub scope: OutboundScope,
+    #[serde(flatten)]
+    pub limit: OutboundLimit,
+    /// Whether an admin set the limits, rather than the environment
+    pub customized: bool,
+    /// Requests holding a concurrency slot right now
+    pub in_flight: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_round_trip() {
+        for scope in OutboundScope::ALL {
+            assert_eq!(OutboundScope::try_from(scope.to_string()), Ok(scope));
+            assert_eq!(serde_json::to_value(scope).unwrap(), serde_json::json!(scope.to_string()));
+        }
+        assert!(OutboundScope::try_from("nextcloud".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_limit_from_env_and_validation() {
+        let env = |name: &str| match name {
+            "OUTBOUND_LLM_MAX_CONCURRENT" => Some("2".to_string()),
+            "OUTBOUND_LLM_REQUESTS_PER_MINUTE" => Some("-5".to_string()),
+            _ => None,
+        };
+        let limit = OutboundLimit::from_lookup(OutboundScope::Llm.env_prefix(), env);
+        assert_eq!(limit, OutboundLimit { max_concurrent: Some(2), ..Default::default() });
+        assert!(limit.validate().is_ok());
+
+        assert!(OutboundLimit { max_concurrent: Some(0), ..Default::default() }.validate().is_err());
+        assert!(OutboundLimit { burst: Some(5), ..Default::default() }.validate().is_err());
+        let rate = OutboundLimit { requests_per_minute: Some(60), burst: Some(5), ..Default::default() };
+        assert!(rate.validate().is_ok());
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum ArtifactKind {
+    #[serde(rename = "stamp")]
+    Stamp,
+    #[serde(rename = "signature")]
+    Signature,
+    #[serde(rename = "figure")]
+    Figure,
+}
+
+impl std::fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArtifactKind::Stamp => write!(f, "stamp"),
+            ArtifactKind::Signature => write!(f, "signature"),
+            ArtifactKind::Figure => write!(f, "figure"),
+        }
+    }
+}
+
+impl TryFrom<String> for ArtifactKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "stamp" => Ok(ArtifactKind::Stamp),
+            "signature" => Ok(ArtifactKind::Signature),
+            "figure" => Ok(ArtifactKind::Figure),
+            _ => Err(format!("Invalid artifact kind: {}", value)),
+        }
+    }
+}
+
+/// Pixel region on a rasterized page
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct BoundingBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PageArtifact {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub page_number: i32,
+    #[sqlx(try_from = "String")]
+    pub kind: ArtifactKind,
+    #[schema(value_type = BoundingBox)]
+    pub bbox: sqlx::types::Json<BoundingBox>,
+    pub detection_score: f32,
+    pub ocr_text: Option<String>,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreatePageArtifact {
+    pub document_id: Uuid,
+    pub page_number: i32,
+    pub kind: ArtifactKind,
+    pub bbox: BoundingBox,
+    pub detection_score: f32,
+    pub ocr_text: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct PageArtifactSearchQuery {
+    /// Restrict to one artifact kind (stamp, signature, figure)
+    pub kind: Option<ArtifactKind>,
+    /// Text to match against the artifact's OCR text and description
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
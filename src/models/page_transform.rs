@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+pub const TRANSFORM_SOURCE_AUTO: &str = "auto";
+pub const TRANSFORM_SOURCE_MANUAL: &str = "manual";
+
+/// Largest skew corrected, in degrees either way
+pub const MAX_SKEW_DEGREES: f32 = 10.0;
+
+/// How a page is turned upright before it is OCRed, rendered or put into a searchable
+/// PDF: a clockwise rotation by quarter turns, then a counter-clockwise deskew. The
+/// stored file is left as it is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PageTransform {
+    pub document_id: Uuid,
+    /// 1-based
+    pub page_number: i32,
+    /// Clockwise rotation in degrees: 0, 90, 180 or 270
+    pub rotation: i32,
+    /// Counter-clockwise correction in degrees, applied after the rotation
+    pub skew_degrees: f32,
+    /// `auto` when detected, `manual` when set by hand; detection never overrides manual
+    pub source: String,
+    /// Orientation confidence reported by the detection
+    pub confidence: Option<f32>,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PageTransform {
+    pub fn identity(document_id: Uuid, page_number: i32) -> Self {
+        Self {
+            document_id,
+            page_number,
+            rotation: 0,
+            skew_degrees: 0.0,
+            source: TRANSFORM_SOURCE_AUTO.to_string(),
+            confidence: None,
+            updated_by: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Whether applying the transform leaves the page as it is
+    pub fn is_identity(&self) -> bool {
+        self.rotation == 0 && self.skew_degrees == 0.0
+    }
+
+    /// The transform turned by a further `degrees` clockwise and set by `user_id`
+    pub fn rotated_by(&self, degrees: i32, reset_skew: bool, user_id: Uuid) -> Self {
+        Self {
+            rotation: normalize_rotation(self.rotation + degrees).unwrap_or(self.rotation),
+            skew_degrees: if reset_skew { 0.0 } else { self.skew_degrees },
+            source: TRANSFORM_SOURCE_MANUAL.to_string(),
+            confidence: None,
+            updated_by: Some(user_id),
+            updated_at: Utc::now(),
+            ..self.clone()
+        }
+    }
+}
+
+/// A rotation in quarter turns as 0, 90, 180 or 270; None when not a quarter turn
+pub fn normalize_rotation(degrees: i32) -> Option<i32> {
+    (degrees % 90 == 0).then(|| degrees.rem_euclid(360))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RotatePagesRequest {
+    /// 1-based pages to rotate; all pages when left out
+    pub pages: Option<Vec<u32>>,
+    /// Clockwise degrees to turn the pages by, a multiple of 90; negative turns
+    /// counter-clockwise
+    pub rotation: i32,
+    /// Also drop the detected deskew of the pages (default: false)
+    #[serde(default)]
+    pub reset_skew: bool,
+}
+
+impl RotatePagesRequest {
+    /// The pages to rotate, checked against the document's page count
+    pub fn pages(&self, page_count: u32) -> Result<Vec<u32>, String> {
+        if normalize_rotation(self.rotation).is_none() {
+            return Err("The rotation must be a multiple of 90 degrees".to_string());
+        }
+        let Some(pages) = &self.pages else {
+            return Ok((1..=page_count).collect());
+        };
+        if pages.is_empty() {
+            return Err("No pages given".to_string());
+        }
+        if let Some(page) = pages.iter().find(|&&page| page == 0 || page > page_count) {
+            return Err(format!("Page {} does not exist", page));
+        }
+        let mut pages = pages.clone();
+        pages.sort_unstable();
+        pages.dedup();
+        Ok(pages)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct PageImageQuery {
+    /// Resolution of the rendered page (default 150, at most 300)
+    pub dpi: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_rotation() {
+        assert_eq!(normalize_rotation(0), Some(0));
+        assert_eq!(normalize_rotation(450), Some(90));
+        assert_eq!(normalize_rotation(-90), Some(270));
+        assert_eq!(normalize_rotation(45), None);
+    }
+
+    #[test]
+    fn test_rotated_by() {
+        let user_id = Uuid::new_v4();
+        let detected = PageTransform { rotation: 90, skew_degrees: 1.5, ..PageTransform::identity(Uuid::new_v4(), 2) };
+
+        let turned = detected.rotated_by(-180, false, user_id);
+        assert_eq!(turned.rotation, 270);
+        assert_eq!(turned.skew_degrees, 1.5);
+        assert_eq!(turned.source, TRANSFORM_SOURCE_MANUAL);
+        assert_eq!(turned.updated_by, Some(user_id));
+
+        let upright = detected.rotated_by(270, true, user_id);
+        assert!(upright.is_identity());
+    }
+
+    #[test]
+    fn test_rotate_pages_request() {
+        let request = RotatePagesRequest { pages: None, rotation: 90, reset_skew: false };
+        assert_eq!(request.pages(3), Ok(vec![1, 2, 3]));
+
+        let some = RotatePagesRequest { pages: Some(vec![3, 1, 3]), ..request.clone() };
+        assert_eq!(some.pages(3), Ok(vec![1, 3]));
+        assert!(RotatePagesRequest { pages: Some(vec![4]), ..request.clone() }.pages(3).is_err());
+        assert!(RotatePagesRequest { pages: Some(vec![]), ..request.clone() }.pages(3).is_err());
+        assert!(RotatePagesRequest { rotation: 45, ..request }.pages(3).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// A WebAuthn passkey registered by a user. The stored credential is not returned.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct UserPasskey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[serde(skip)]
+    pub credential_id: Vec<u8>,
+    #[serde(skip)]
+    pub passkey: serde_json::Value,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Options to pass to `navigator.credentials.create()` or `.get()`, and the id of the
+/// ceremony to send back with the browser's answer
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PasskeyChallengeResponse {
+    pub ceremony_id: Uuid,
+    #[schema(value_type = Object)]
+    pub options: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct FinishPasskeyRegistrationRequest {
+    pub ceremony_id: Uuid,
+    /// Label to tell passkeys apart, e.g. "Work laptop"
+    pub name: String,
+    /// The `PublicKeyCredential` returned by `navigator.credentials.create()`
+    #[schema(value_type = Object)]
+    pub credential: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct StartPasskeyLoginRequest {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct FinishPasskeyLoginRequest {
+    pub ceremony_id: Uuid,
+    /// The `PublicKeyCredential` returned by `navigator.credentials.get()`
+    #[schema(value_type = Object)]
+    pub credential: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct RenamePasskeyRequest {
+    pub name: String,
+}
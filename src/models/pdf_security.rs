@@ -0,0 +1,143 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Key of a document's `source_metadata` that holds its content security report
+pub const PDF_SECURITY_METADATA_KEY: &str = "pdf_security";
+
+/// Active content a PDF can carry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PdfSecurityFindingKind {
+    /// Scripts run by the viewer, on opening the file or on an action
+    Javascript,
+    /// Actions that start another program or open another file
+    LaunchAction,
+    /// Files embedded in the document
+    EmbeddedFile,
+    /// Annotations on a page that hold a file
+    FileAttachment,
+    /// Form actions that send field values to a web address
+    SubmitForm,
+    /// Embedded Flash, video or 3D content
+    RichMedia,
+    /// XML form data, which can carry scripts of its own
+    Xfa,
+}
+
+impl PdfSecurityFindingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PdfSecurityFindingKind::Javascript => "javascript",
+            PdfSecurityFindingKind::LaunchAction => "launch_action",
+            PdfSecurityFindingKind::EmbeddedFile => "embedded_file",
+            PdfSecurityFindingKind::FileAttachment => "file_attachment",
+            PdfSecurityFindingKind::SubmitForm => "submit_form",
+            PdfSecurityFindingKind::RichMedia => "rich_media",
+            PdfSecurityFindingKind::Xfa => "xfa",
+        }
+    }
+}
+
+/// One kind of active content found in a PDF
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PdfSecurityFinding {
+    pub kind: PdfSecurityFindingKind,
+    /// Times the content's markers appear in the file
+    pub occurrences: u32,
+}
+
+/// The active content of a PDF as scanned at ingestion, and whether it was stripped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PdfSecurityReport {
+    #[serde(default)]
+    pub findings: Vec<PdfSecurityFinding>,
+    /// Whether the file keeps objects in compressed streams, where the scan only finds
+    /// embedded files
+    #[serde(default)]
+    pub compressed_objects: bool,
+    /// None when the document was never scanned
+    pub scanned_at: Option<DateTime<Utc>>,
+    /// SHA-256 of the file that was scanned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_hash: Option<String>,
+    /// SHA-256 of the copy the active content was stripped from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sanitized_file_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sanitized_at: Option<DateTime<Utc>>,
+    /// Why the active content was not stripped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sanitize_error: Option<String>,
+}
+
+impl PdfSecurityReport {
+    pub fn unscanned() -> Self {
+        Self {
+            findings: Vec::new(),
+            compressed_objects: false,
+            scanned_at: None,
+            file_hash: None,
+            sanitized_file_hash: None,
+            sanitized_at: None,
+            sanitize_error: None,
+        }
+    }
+
+    pub fn scanned(findings: Vec<PdfSecurityFinding>, compressed_objects: bool, now: DateTime<Utc>) -> Self {
+        Self { findings, compressed_objects, scanned_at: Some(now), ..Self::unscanned() }
+    }
+
+    pub fn has_findings(&self) -> bool {
+        !self.findings.is_empty()
+    }
+
+    /// Whether a file with `file_hash` is the scanned original with its active content
+    /// still in it, e.g. because stripping is off or the original version was restored
+    pub fn is_unsanitized(&self, file_hash: Option<&str>) -> bool {
+        self.has_findings() && file_hash.is_some() && file_hash == self.file_hash.as_deref()
+    }
+
+    /// The kinds found, for warnings: e.g. `javascript, embedded_file`
+    pub fn kinds(&self) -> String {
+        self.findings.iter().map(|finding| finding.kind.as_str()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(kind: PdfSecurityFindingKind) -> PdfSecurityFinding {
+        PdfSecurityFinding { kind, occurrences: 1 }
+    }
+
+    #[test]
+    fn test_is_unsanitized() {
+        let report = PdfSecurityReport {
+            file_hash: Some("original".to_string()),
+            sanitized_file_hash: Some("sanitized".to_string()),
+            ..PdfSecurityReport::scanned(vec![finding(PdfSecurityFindingKind::Javascript)], false, Utc::now())
+        };
+        assert!(report.is_unsanitized(Some("original")));
+        assert!(!report.is_unsanitized(Some("sanitized")));
+        assert!(!report.is_unsanitized(Some("later version")));
+        assert!(!report.is_unsanitized(None));
+
+        let clean = PdfSecurityReport { file_hash: Some("original".to_string()), ..PdfSecurityReport::scanned(Vec::new(), true, Utc::now()) };
+        assert!(!clean.is_unsanitized(Some("original")));
+    }
+
+    #[test]
+    fn test_kinds() {
+        let report = PdfSecurityReport::scanned(
+            vec![finding(PdfSecurityFindingKind::Javascript), finding(PdfSecurityFindingKind::EmbeddedFile)],
+            false,
+            Utc::now(),
+        );
+        assert_eq!(report.kinds(), "javascript, embedded_file");
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["findings"][1]["kind"], "embedded_file");
+        assert!(value.get("sanitized_file_hash").is_none());
+    }
+}
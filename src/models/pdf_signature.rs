@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Key of a document's `source_metadata` that holds its signature report
+pub const PDF_SIGNATURES_METADATA_KEY: &str = "pdf_signatures";
+
+/// What the signatures of a PDF say about it as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PdfSignatureStatus {
+    /// The PDF has no signatures
+    Unsigned,
+    /// Every signature matches the content it covers, and the newest covers the whole file
+    Valid,
+    /// The signatures match, but content was added after the newest one, e.g. by
+    /// filling in a form
+    ModifiedAfterSigning,
+    /// A signature does not match the content it covers
+    Invalid,
+    /// The PDF was validly signed when it was ingested, but a later change in Readur
+    /// (a redaction, page edit or new version) invalidated or removed the signatures
+    Broken,
+    /// The signatures could not be checked
+    Unverified,
+}
+
+impl PdfSignatureStatus {
+    /// Whether the signatures held up when they were checked
+    pub fn is_intact(self) -> bool {
+        matches!(self, PdfSignatureStatus::Valid | PdfSignatureStatus::ModifiedAfterSigning)
+    }
+}
+
+/// One signature embedded in a PDF
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PdfSignature {
+    /// Common name of the signer's certificate
+    pub signer_name: Option<String>,
+    /// Full distinguished name of the signer's certificate
+    pub signer_dn: Option<String>,
+    /// Signing time claimed by the signature, as the signer's software wrote it
+    pub signing_time: Option<String>,
+    pub hash_algorithm: Option<String>,
+    /// E.g. `adbe.pkcs7.detached` or `ETSI.CAdES.detached`
+    pub signature_type: Option<String>,
+    /// Whether the signed bytes still match the signature
+    pub signature_valid: bool,
+    /// The verifier's verdict on the signature, e.g. "Signature is Valid."
+    pub signature_status: String,
+    /// Whether the signing certificate chains to a trusted issuer
+    pub certificate_trusted: bool,
+    /// The verifier's verdict on the certificate, e.g. "Certificate issuer isn't Trusted."
+    pub certificate_status: Option<String>,
+    /// Whether the signature covers the whole file rather than an earlier revision of it
+    pub covers_whole_document: bool,
+}
+
+/// The signatures of a PDF as last checked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PdfSignatureReport {
+    pub status: PdfSignatureStatus,
+    #[serde(default)]
+    pub signatures: Vec<PdfSignature>,
+    /// None when the document was never checked
+    pub checked_at: Option<DateTime<Utc>>,
+    /// SHA-256 of the file that was checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_hash: Option<String>,
+    /// Why the signatures could not be checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The signatures as they were before the change that broke them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub original_signatures: Vec<PdfSignature>,
+    /// When a change was found to have broken the signatures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broken_at: Option<DateTime<Utc>>,
+}
+
+impl PdfSignatureReport {
+    pub fn unchecked() -> Self {
+        Self {
+            status: PdfSignatureStatus::Unsigned,
+            signatures: Vec::new(),
+            checked_at: None,
+            file_hash: None,
+            error: None,
+            original_signatures: Vec::new(),
+            broken_at: None,
+        }
+    }
+
+    pub fn checked(signatures: Vec<PdfSignature>, now: DateTime<Utc>) -> Self {
+        Self { status: status_of(&signatures), signatures, checked_at: Some(now), ..Self::unchecked() }
+    }
+
+    pub fn unverified(error: String, now: DateTime<Utc>) -> Self {
+        Self {
+            status: PdfSignatureStatus::Unverified,
+            checked_at: Some(now),
+            error: Some(error),
+            ..Self::unchecked()
+        }
+    }
+
+    /// The report for the current contents of a document that had `previous` as its
+    /// report: signatures that held up before and no longer do are broken
+    pub fn after_change(self, previous: &PdfSignatureReport, now: DateTime<Utc>) -> Self {
+        let was_intact = previous.status.is_intact() || previous.status == PdfSignatureStatus::Broken;
+        let now_failing = matches!(self.status, PdfSignatureStatus::Unsigned | PdfSignatureStatus::Invalid);
+        if !was_intact || !now_failing {
+            return self;
+        }
+
+        let original_signatures = if previous.status == PdfSignatureStatus::Broken {
+            previous.original_signatures.clone()
+        } else {
+            previous.signatures.clone()
+        };
+        Self {
+            status: PdfSignatureStatus::Broken,
+            original_signatures,
+            broken_at: previous.broken_at.or(Some(now)),
+            ..self
+        }
+    }
+}
+
+/// Overall status of a PDF with these signatures, oldest first
+pub fn status_of(signatures: &[PdfSignature]) -> PdfSignatureStatus {
+    match signatures.last() {
+        None => PdfSignatureStatus::Unsigned,
+        Some(_) if signatures.iter().any(|s| !s.signature_valid) => PdfSignatureStatus::Invalid,
+        Some(newest) if !newest.covers_whole_document => PdfSignatureStatus::ModifiedAfterSigning,
+        Some(_) => PdfSignatureStatus::Valid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(valid: bool, whole: bool) -> PdfSignature {
+        PdfSignature {
+            signer_name: Some("Jane Doe".to_string()),
+            signer_dn: None,
+            signing_time: None,
+            hash_algorithm: None,
+            signature_type: None,
+            signature_valid: valid,
+            signature_status: String::new(),
+            certificate_trusted: false,
+            certificate_status: None,
+            covers_whole_document: whole,
+        }
+    }
+
+    #[test]
+    fn test_signature_status() {
+        assert_eq!(status_of(&[]), PdfSignatureStatus::Unsigned);
+        assert_eq!(status_of(&[signature(true, true)]), PdfSignatureStatus::Valid);
+        assert_eq!(status_of(&[signature(true, false)]), PdfSignatureStatus::ModifiedAfterSigning);
+        assert_eq!(status_of(&[signature(true, false), signature(true, true)]), PdfSignatureStatus::Valid);
+        assert_eq!(status_of(&[signature(false, false), signature(true, true)]), PdfSignatureStatus::Invalid);
+    }
+
+    #[test]
+    fn test_signatures_broken_by_change() {
+        let now = Utc::now();
+        let ingested = PdfSignatureReport::checked(vec![signature(true, true)], now);
+
+        let redacted = PdfSignatureReport::checked(vec![], now).after_change(&ingested, now);
+        assert_eq!(redacted.status, PdfSignatureStatus::Broken);
+        assert_eq!(redacted.original_signatures, ingested.signatures);
+        assert_eq!(redacted.broken_at, Some(now));
+
+        // Restoring the signed original makes the signatures valid again
+        let restored = PdfSignatureReport::checked(vec![signature(true, true)], now).after_change(&redacted, now);
+        assert_eq!(restored.status, PdfSignatureStatus::Valid);
+        assert!(restored.original_signatures.is_empty());
+
+        // A document that was never validly signed is not broken by a change
+        let invalid = PdfSignatureReport::checked(vec![signature(false, true)], now);
+        let edited = PdfSignatureReport::checked(vec![], now).after_change(&invalid, now);
+        assert_eq!(edited.status, PdfSignatureStatus::Unsigned);
+    }
+}
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Kinds of physical location, outermost first
+pub const PHYSICAL_LOCATION_KINDS: [&str; 3] = ["shelf", "box", "binder"];
+
+/// A shelf, box or binder where paper originals are kept
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PhysicalLocation {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Enclosing location, e.g. the box a binder is in
+    pub parent_id: Option<Uuid>,
+    /// `shelf`, `box` or `binder`
+    pub kind: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreatePhysicalLocationRequest {
+    pub parent_id: Option<Uuid>,
+    pub kind: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdatePhysicalLocationRequest {
+    pub kind: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Set the location of documents' originals; `location_id` of null clears it
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AssignPhysicalLocationRequest {
+    pub document_ids: Vec<Uuid>,
+    pub location_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AssignPhysicalLocationResponse {
+    pub updated: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct PhysicalLocationDocumentsQuery {
+    /// Also list documents in locations inside this one
+    #[serde(default)]
+    pub include_sublocations: bool,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PhysicalLocationStats {
+    pub location_id: Uuid,
+    pub kind: String,
+    /// Names from the outermost location down, e.g. `Shelf A / Box 3 / Taxes 2019`
+    pub path: String,
+    /// Documents assigned to this location itself
+    pub document_count: i64,
+    /// Documents in this location and every location inside it
+    pub total_document_count: i64,
+    pub total_size_bytes: i64,
+    pub oldest_document_date: Option<DateTime<Utc>>,
+    pub newest_document_date: Option<DateTime<Utc>>,
+    /// Documents in this location and inside it that are past the retention period
+    pub due_for_destruction: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PhysicalLocationStatsResponse {
+    pub locations: Vec<PhysicalLocationStats>,
+    /// Documents without a physical location
+    pub unassigned_documents: i64,
+    /// The user's retention period; nothing is due when it is not set
+    pub retention_days: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RetentionDueDocument {
+    pub document_id: Uuid,
+    pub original_filename: String,
+    /// Original creation date when known, otherwise the upload date
+    pub document_date: DateTime<Utc>,
+    pub destroy_after: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RetentionLocationReport {
+    pub location_id: Uuid,
+    pub path: String,
+    pub documents: Vec<RetentionDueDocument>,
+}
+
+/// Which physical locations hold originals that are past the retention period
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RetentionReport {
+    pub retention_days: Option<i32>,
+    pub locations: Vec<RetentionLocationReport>,
+}
+
+/// Each location's path from the outermost enclosing location, e.g. `Shelf A / Box 3`
+pub fn location_paths(locations: &[PhysicalLocation]) -> HashMap<Uuid, String> {
+    let by_id: HashMap<Uuid, &PhysicalLocation> = locations.iter().map(|l| (l.id, l)).collect();
+    locations
+        .iter()
+        .map(|location| {
+            let names: Vec<&str> = location_chain(&by_id, location.id)
+                .iter()
+                .rev()
+                .map(|id| by_id[id].name.as_str())
+                .collect();
+            (location.id, names.join(" / "))
+        })
+        .collect()
+}
+
+/// The location followed by the locations enclosing it, innermost first
+pub fn location_chain(by_id: &HashMap<Uuid, &PhysicalLocation>, id: Uuid) -> Vec<Uuid> {
+    let mut chain = Vec::new();
+    let mut current = Some(id);
+    while let Some(id) = current {
+        // A cycle cannot be created through the API, but must not hang the walk
+        if chain.contains(&id) || !by_id.contains_key(&id) {
+            break;
+        }
+        chain.push(id);
+        current = by_id[&id].parent_id;
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(name: &str, kind: &str, parent_id: Option<Uuid>) -> PhysicalLocation {
+        PhysicalLocation {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            parent_id,
+            kind: kind.to_string(),
+            name: name.to_string(),
+            description: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_location_paths() {
+        let shelf = location("Shelf A", "shelf", None);
+        let in_box = location("Box 3", "box", Some(shelf.id));
+        let binder = location("Taxes 2019", "binder", Some(in_box.id));
+        let binder_id = binder.id;
+        let locations = vec![binder, shelf, in_box];
+
+        let paths = location_paths(&locations);
+        assert_eq!(paths[&binder_id], "Shelf A / Box 3 / Taxes 2019");
+        assert_eq!(paths[&locations[1].id], "Shelf A");
+
+        let by_id: HashMap<Uuid, &PhysicalLocation> = locations.iter().map(|l| (l.id, l)).collect();
+        assert_eq!(location_chain(&by_id, binder_id), vec![binder_id, locations[2].id, locations[1].id]);
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use super::RedactDocumentRequest;
+
+/// Kinds of personal data looked for in document text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "pii_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    /// US social security number
+    Ssn,
+    Iban,
+    CreditCard,
+    Email,
+    Phone,
+}
+
+impl PiiKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace([' ', '-'], "_").as_str() {
+            "ssn" => Some(PiiKind::Ssn),
+            "iban" => Some(PiiKind::Iban),
+            "credit_card" => Some(PiiKind::CreditCard),
+            "email" => Some(PiiKind::Email),
+            "phone" => Some(PiiKind::Phone),
+            _ => None,
+        }
+    }
+}
+
+/// Personal data found in a document's OCR text
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PiiFinding {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub kind: PiiKind,
+    /// The text as it appears in the document
+    pub text: String,
+    /// Byte offsets of the text in the document's OCR text
+    pub start_offset: i32,
+    pub end_offset: i32,
+    /// 1-based page, for paginated text
+    pub page_number: Option<i32>,
+    /// `regex` or `llm`
+    pub detected_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A finding about to be stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewPiiFinding {
+    pub kind: PiiKind,
+    pub text: String,
+    pub start_offset: i32,
+    pub end_offset: i32,
+    pub page_number: Option<i32>,
+    pub detected_by: &'static str,
+}
+
+/// The personal data found in a document, and a redaction that would remove it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentPiiResponse {
+    pub document_id: Uuid,
+    /// None until the document was scanned
+    pub scanned_at: Option<DateTime<Utc>>,
+    /// Whether the LLM looked for personal data as well as the patterns
+    pub llm_used: bool,
+    pub findings: Vec<PiiFinding>,
+    /// Body for `POST /api/documents/{id}/redact` that blacks out every finding
+    pub suggested_redaction: RedactDocumentRequest,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct PiiScanQuery {
+    /// Also ask the LLM (default: `PII_SCAN_USE_LLM`)
+    pub use_llm: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pii_kind_from_name() {
+        assert_eq!(PiiKind::from_name("Credit Card"), Some(PiiKind::CreditCard));
+        assert_eq!(PiiKind::from_name("credit-card"), Some(PiiKind::CreditCard));
+        assert_eq!(PiiKind::from_name("IBAN"), Some(PiiKind::Iban));
+        assert_eq!(PiiKind::from_name("passport"), None);
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// What processing a document took since it was uploaded
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentProcessingCosts {
+    pub ocr_runs: i64,
+    pub ocr_seconds: f64,
+    /// Pages OCRed over all runs, so a document OCRed twice counts its pages twice
+    pub ocr_pages: i64,
+    pub llm_requests: i64,
+    pub llm_seconds: f64,
+    pub llm_prompt_tokens: i64,
+    pub llm_completion_tokens: i64,
+    /// In the currency of the configured token prices; None when no prices were set
+    pub llm_cost: Option<f64>,
+    /// The file, its earlier versions and an original kept after recompression
+    pub storage_bytes: i64,
+}
+
+/// Measure documents of the report are ranked by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingCostSort {
+    #[default]
+    OcrSeconds,
+    OcrPages,
+    LlmTokens,
+    LlmCost,
+    StorageBytes,
+}
+
+impl ProcessingCostSort {
+    /// Column of the per-document query to order by
+    pub fn column(&self) -> &'static str {
+        match self {
+            ProcessingCostSort::OcrSeconds => "ocr_seconds",
+            ProcessingCostSort::OcrPages => "ocr_pages",
+            ProcessingCostSort::LlmTokens => "llm_prompt_tokens + llm_completion_tokens",
+            ProcessingCostSort::LlmCost => "COALESCE(llm_cost, 0)",
+            ProcessingCostSort::StorageBytes => "storage_bytes",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct ProcessingCostQuery {
+    /// Processing of the last days counted (default 30, at most 365)
+    pub days: Option<i32>,
+    /// Measure documents are ranked by (default `ocr_seconds`)
+    pub sort_by: Option<ProcessingCostSort>,
+    /// Documents and users listed (default 20, at most 100)
+    pub limit: Option<i64>,
+}
+
+/// Processing of all documents over the period
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ProcessingCostTotals {
+    /// Documents processed in the period
+    pub documents: i64,
+    pub ocr_runs: i64,
+    pub ocr_seconds: f64,
+    pub ocr_pages: i64,
+    pub llm_requests: i64,
+    pub llm_seconds: f64,
+    pub llm_prompt_tokens: i64,
+    pub llm_completion_tokens: i64,
+    pub llm_cost: Option<f64>,
+    /// Storage of the documents processed in the period
+    pub storage_bytes: i64,
+    /// What a cloud OCR service would have charged for the pages, at
+    /// `OCR_COST_PER_1K_PAGES`; None when no price is set
+    #[sqlx(default)]
+    pub estimated_ocr_cost: Option<f64>,
+}
+
+impl ProcessingCostTotals {
+    /// Price the OCRed pages at `price` per 1,000 pages
+    pub fn with_ocr_price(mut self, price: Option<f64>) -> Self {
+        self.estimated_ocr_cost = price.map(|price| self.ocr_pages as f64 * price / 1000.0);
+        self
+    }
+}
+
+/// A user's documents processed in the period
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserProcessingCosts {
+    pub user_id: Uuid,
+    pub username: String,
+    pub documents: i64,
+    pub ocr_seconds: f64,
+    pub ocr_pages: i64,
+    pub llm_tokens: i64,
+    pub llm_cost: Option<f64>,
+}
+
+/// A document processed in the period, with what its processing took
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentProcessingCostEntry {
+    pub document_id: Uuid,
+    pub filename: String,
+    pub mime_type: String,
+    pub username: String,
+    pub ocr_runs: i64,
+    pub ocr_seconds: f64,
+    pub ocr_pages: i64,
+    pub llm_requests: i64,
+    pub llm_prompt_tokens: i64,
+    pub llm_completion_tokens: i64,
+    pub llm_cost: Option<f64>,
+    pub storage_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProcessingCostReport {
+    pub days: i32,
+    pub sort_by: ProcessingCostSort,
+    pub totals: ProcessingCostTotals,
+    /// Users whose documents took the most OCR time
+    pub users: Vec<UserProcessingCosts>,
+    /// Documents ranked by `sort_by`
+    pub documents: Vec<DocumentProcessingCostEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ocr_price_is_per_thousand_pages() {
+        let totals = ProcessingCostTotals { ocr_pages: 2500, ..Default::default() };
+        assert_eq!(totals.clone().with_ocr_price(Some(1.5)).estimated_ocr_cost, Some(3.75));
+        assert_eq!(totals.with_ocr_price(None).estimated_ocr_cost, None);
+    }
+
+    #[test]
+    fn test_sort_parses_snake_case() {
+        let sort: ProcessingCostSort = serde_json::from_str("\"llm_tokens\"").unwrap();
+        assert_eq!(sort, ProcessingCostSort::LlmTokens);
+        assert_eq!(ProcessingCostSort::default().column(), "ocr_seconds");
+    }
+}
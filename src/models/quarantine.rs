@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// A file the malware scanner found infected, kept out of the library
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct QuarantinedFile {
+    pub id: Uuid,
+    /// The user the file was ingested for
+    pub user_id: Uuid,
+    pub filename: String,
+    pub original_filename: String,
+    /// Where the file is kept on the local disk
+    #[serde(skip_serializing)]
+    pub file_path: String,
+    pub file_size: i64,
+    pub file_hash: String,
+    pub mime_type: String,
+    /// Signature the scanner reported
+    pub signature: String,
+    /// How the file came in, e.g. "web_upload" or "webdav"
+    pub source_type: Option<String>,
+    pub source_id: Option<Uuid>,
+    pub source_path: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct QuarantineListQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A content an external converter was killed on, and whether it is quarantined
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ConverterQuarantineEntry {
+    /// SHA-256 of the content, as in `documents.file_hash`
+    pub file_hash: String,
+    /// The converter last killed on it, e.g. "soffice"
+    pub tool: String,
+    /// The limit it exceeded last
+    pub reason: String,
+    /// Times a converter was killed on it
+    pub strikes: i32,
+    /// Documents with this content, at most 10
+    pub documents: Vec<String>,
+    pub first_killed_at: DateTime<Utc>,
+    pub last_killed_at: DateTime<Utc>,
+    /// When it reached the strike limit; no converter runs on it since
+    pub quarantined_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ConverterQuarantineQuery {
+    /// Also list contents killed fewer times than the strike limit
+    #[serde(default)]
+    pub include_strikes: bool,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
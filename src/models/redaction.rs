@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::DocumentResponse;
+
+/// Most regions and terms accepted in one redaction
+pub const MAX_REDACTION_REGIONS: usize = 500;
+pub const MAX_REDACTION_TERMS: usize = 100;
+const MAX_TERM_LENGTH: usize = 200;
+
+/// An area of a page to black out, as fractions (0-1) of the page's width and height
+/// measured from the top-left corner
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct RedactionRegion {
+    /// 1-based page number; images have a single page
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl RedactionRegion {
+    fn is_within_page(&self) -> bool {
+        [self.x, self.y, self.width, self.height].iter().all(|v| v.is_finite())
+            && self.x >= 0.0
+            && self.y >= 0.0
+            && self.width > 0.0
+            && self.height > 0.0
+            && self.x + self.width <= 1.0 + 1e-6
+            && self.y + self.height <= 1.0 + 1e-6
+    }
+}
+
+/// What to remove from a document: areas of pages, and names or other phrases
+/// wherever they appear
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RedactDocumentRequest {
+    #[serde(default)]
+    pub regions: Vec<RedactionRegion>,
+    /// Names, numbers or phrases to black out on every page, ignoring case
+    #[serde(default)]
+    pub terms: Vec<String>,
+}
+
+impl RedactDocumentRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.regions.is_empty() && self.terms.iter().all(|term| term.trim().is_empty()) {
+            return Err("Nothing to redact: give regions or terms".to_string());
+        }
+        if self.regions.len() > MAX_REDACTION_REGIONS {
+            return Err(format!("At most {} regions can be redacted at once", MAX_REDACTION_REGIONS));
+        }
+        if self.terms.len() > MAX_REDACTION_TERMS {
+            return Err(format!("At most {} terms can be redacted at once", MAX_REDACTION_TERMS));
+        }
+        // The terms themselves are what should not end up anywhere, so not in errors either
+        if self.terms.iter().any(|term| term.chars().count() > MAX_TERM_LENGTH) {
+            return Err(format!("A term is longer than {} characters", MAX_TERM_LENGTH));
+        }
+        if self.regions.iter().any(|region| region.page == 0) {
+            return Err("Pages are numbered from 1".to_string());
+        }
+        if let Some(region) = self.regions.iter().find(|region| !region.is_within_page()) {
+            return Err(format!("A region on page {} is empty or reaches past the page", region.page));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RedactDocumentResponse {
+    /// The document with its redacted contents, queued for OCR again
+    pub document: DocumentResponse,
+    /// Version number under which the unredacted contents were kept
+    pub original_version: i32,
+    pub regions_redacted: usize,
+    /// Places on the pages where one of the terms was found and blacked out
+    pub term_matches: usize,
+    pub pages_redacted: Vec<u32>,
+    pub redacted_by: Uuid,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_request_validation() {
+        let region = RedactionRegion { page: 1, x: 0.1, y: 0.1, width: 0.2, height: 0.05 };
+        assert!(RedactDocumentRequest { regions: vec![region], terms: vec![] }.validate().is_ok());
+        assert!(RedactDocumentRequest { regions: vec![], terms: vec!["Jane Doe".to_string()] }.validate().is_ok());
+
+        assert!(RedactDocumentRequest::default().validate().is_err());
+        assert!(RedactDocumentRequest { regions: vec![], terms: vec!["  ".to_string()] }.validate().is_err());
+        let page_zero = RedactionRegion { page: 0, ..region };
+        assert!(RedactDocumentRequest { regions: vec![page_zero], terms: vec![] }.validate().is_err());
+        let off_page = RedactionRegion { x: 0.9, ..region };
+        assert!(RedactDocumentRequest { regions: vec![off_page], terms: vec![] }.validate().is_err());
+    }
+}
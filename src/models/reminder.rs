@@ -0,0 +1,146 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+const MAX_REMINDER_NOTE_LENGTH: usize = 1000;
+/// Reminders may be set slightly in the past, for clocks that are a little off
+const MAX_CLOCK_SKEW_MINUTES: i64 = 5;
+/// Snoozing without saying for how long waits a day
+pub const DEFAULT_SNOOZE_MINUTES: i64 = 24 * 60;
+/// Longest snooze, a year
+const MAX_SNOOZE_MINUTES: i64 = 365 * 24 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "reminder_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReminderStatus {
+    /// Waiting for its time
+    Pending,
+    /// The notification went out; the reminder stays until it is completed or snoozed
+    Sent,
+    Completed,
+}
+
+/// A reminder a user set on a document
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentReminder {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub document_filename: String,
+    pub user_id: Uuid,
+    /// What to do, e.g. "Review before the renewal deadline"
+    pub note: Option<String>,
+    pub remind_at: DateTime<Utc>,
+    pub status: ReminderStatus,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// How often the reminder was put off
+    pub snooze_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct ReminderListQuery {
+    /// Only reminders with this status; all of them by default
+    pub status: Option<ReminderStatus>,
+}
+
+fn validate_note(note: Option<&str>) -> Result<(), String> {
+    if note.is_some_and(|note| note.chars().count() > MAX_REMINDER_NOTE_LENGTH) {
+        return Err(format!("Notes are limited to {} characters", MAX_REMINDER_NOTE_LENGTH));
+    }
+    Ok(())
+}
+
+fn validate_remind_at(remind_at: DateTime<Utc>, now: DateTime<Utc>) -> Result<(), String> {
+    if remind_at < now - Duration::minutes(MAX_CLOCK_SKEW_MINUTES) {
+        return Err("Reminders cannot be set in the past".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateReminderRequest {
+    pub remind_at: DateTime<Utc>,
+    pub note: Option<String>,
+}
+
+impl CreateReminderRequest {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), String> {
+        validate_remind_at(self.remind_at, now)?;
+        validate_note(self.note.as_deref())
+    }
+}
+
+/// Changes to a reminder. A new time makes a sent or completed reminder pending again.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateReminderRequest {
+    pub remind_at: Option<DateTime<Utc>>,
+    /// An empty note removes it
+    pub note: Option<String>,
+}
+
+impl UpdateReminderRequest {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), String> {
+        if let Some(remind_at) = self.remind_at {
+            validate_remind_at(remind_at, now)?;
+        }
+        validate_note(self.note.as_deref())
+    }
+}
+
+/// Put a reminder off until a time, or for a number of minutes; a day when neither
+/// is given
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct SnoozeReminderRequest {
+    pub until: Option<DateTime<Utc>>,
+    pub minutes: Option<i64>,
+}
+
+impl SnoozeReminderRequest {
+    /// When the reminder is due again
+    pub fn resolve(&self, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        match (self.until, self.minutes) {
+            (Some(_), Some(_)) => Err("Give either until or minutes, not both".to_string()),
+            (Some(until), None) if until <= now => Err("Snooze until a time in the future".to_string()),
+            (Some(until), None) => Ok(until),
+            (None, Some(minutes)) if !(1..=MAX_SNOOZE_MINUTES).contains(&minutes) => {
+                Err(format!("Snooze for 1 to {} minutes", MAX_SNOOZE_MINUTES))
+            }
+            (None, minutes) => Ok(now + Duration::minutes(minutes.unwrap_or(DEFAULT_SNOOZE_MINUTES))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snooze_resolution() {
+        let now = Utc::now();
+        assert_eq!(SnoozeReminderRequest::default().resolve(now), Ok(now + Duration::days(1)));
+        let hour = SnoozeReminderRequest { minutes: Some(60), ..Default::default() };
+        assert_eq!(hour.resolve(now), Ok(now + Duration::hours(1)));
+        let until = now + Duration::days(3);
+        assert_eq!(SnoozeReminderRequest { until: Some(until), minutes: None }.resolve(now), Ok(until));
+
+        assert!(SnoozeReminderRequest { until: Some(now - Duration::hours(1)), minutes: None }.resolve(now).is_err());
+        assert!(SnoozeReminderRequest { until: Some(until), minutes: Some(5) }.resolve(now).is_err());
+        assert!(SnoozeReminderRequest { minutes: Some(0), ..Default::default() }.resolve(now).is_err());
+    }
+
+    #[test]
+    fn test_create_reminder_validation() {
+        let now = Utc::now();
+        let request = CreateReminderRequest { remind_at: now + Duration::days(30), note: Some("Review".to_string()) };
+        assert!(request.validate(now).is_ok());
+        let past = CreateReminderRequest { remind_at: now - Duration::days(1), note: None };
+        assert!(past.validate(now).is_err());
+        let long = CreateReminderRequest { remind_at: now, note: Some("x".repeat(MAX_REMINDER_NOTE_LENGTH + 1)) };
+        assert!(long.validate(now).is_err());
+    }
+}
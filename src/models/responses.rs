@@ -8,22 +8,35 @@ use super::document::Document;
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SearchSnippet {
-    /// The snippet text content
+    /// The snippet text content; page breaks are shown as spaces
     pub text: String,
-    /// Starting character position in the original document
+    /// Starting byte offset in the searched text
     pub start_offset: i32,
-    /// Ending character position in the original document
+    /// Ending byte offset in the searched text
     pub end_offset: i32,
+    /// Starting character offset in the searched text
+    pub start_char: i32,
+    /// Ending character offset in the searched text
+    pub end_char: i32,
+    /// Which text the offsets refer to: `content` or `ocr_text`, or `annotations`,
+    /// `comments` or `custom_fields` for the text collected from those
+    pub source: String,
+    /// Page of the match, for PDFs and other text with page breaks
+    pub page_number: Option<i32>,
     /// Ranges within the snippet that should be highlighted
     pub highlight_ranges: Vec<HighlightRange>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct HighlightRange {
-    /// Start position of highlight within the snippet
+    /// Start byte position of highlight within the snippet
     pub start: i32,
-    /// End position of highlight within the snippet
+    /// End byte position of highlight within the snippet
     pub end: i32,
+    /// Start character position of highlight within the snippet
+    pub start_char: i32,
+    /// End character position of highlight within the snippet
+    pub end_char: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -94,6 +107,16 @@ pub struct DocumentResponse {
     /// Additional metadata from source system (EXIF data, PDF metadata, custom attributes, etc.)
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub source_metadata: Option<serde_json::Value>,
+    /// Relations to other documents; only filled in on the document detail response
+    #[serde(default)]
+    pub relations: Vec<crate::models::LinkedDocumentRelation>,
+    /// Relations the knowledge graph suggests; only filled in on the document detail response
+    #[serde(default)]
+    pub suggested_relations: Vec<crate::models::DocumentRelationSuggestion>,
+    /// OCR time and pages, LLM tokens and storage the document took; only filled in on
+    /// the document detail response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processing_costs: Option<crate::models::DocumentProcessingCosts>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -126,6 +149,9 @@ pub struct EnhancedDocumentResponse {
     pub search_rank: Option<f32>,
     /// Text snippets showing search matches with highlights
     pub snippets: Vec<SearchSnippet>,
+    /// Which parts of the document the search matched
+    #[serde(default)]
+    pub matched_fields: Vec<crate::models::SearchField>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -172,6 +198,9 @@ pub struct DocumentOcrResponse {
     pub has_ocr_text: bool,
     /// OCR text content (if available)
     pub ocr_text: Option<String>,
+    /// The text as OCR returned it, when normalization changed it before it was stored
+    /// and indexed
+    pub ocr_text_raw: Option<String>,
     /// OCR processing confidence score (0-100)
     pub ocr_confidence: Option<f32>,
     /// Current OCR processing status
@@ -182,6 +211,10 @@ pub struct DocumentOcrResponse {
     pub detected_language: Option<String>,
     /// Number of pages processed (for multi-page documents)
     pub pages_processed: Option<i32>,
+    /// When the OCR text was last corrected by hand, if it still is
+    pub corrected_at: Option<DateTime<Utc>>,
+    /// Who corrected it
+    pub corrected_by: Option<Uuid>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -290,6 +323,9 @@ impl From<Document> for DocumentResponse {
             file_owner: doc.file_owner,
             file_group: doc.file_group,
             source_metadata: doc.source_metadata,
+            relations: Vec::new(),
+            suggested_relations: Vec::new(),
+            processing_costs: None,
         }
     }
 }
@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// What a retention policy does with documents past their retention period
+pub const RETENTION_ACTIONS: [&str; 2] = ["archive", "delete"];
+
+/// States of a deletion in the review queue
+pub const RETENTION_REVIEW_STATUSES: [&str; 3] = ["pending", "approved", "exempted"];
+
+/// Archives or deletes a user's documents with a label or of a document type once
+/// they are older than `retain_days`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct RetentionPolicy {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub label_id: Option<Uuid>,
+    /// A MIME type, or a prefix ending in `/` such as `image/`
+    pub mime_type: Option<String>,
+    /// `archive` or `delete`
+    pub action: String,
+    /// Counted from the original creation date when known, otherwise the upload date
+    pub retain_days: i32,
+    /// Days a due deletion waits in the review queue before it is carried out
+    pub grace_days: i32,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateRetentionPolicyRequest {
+    pub name: String,
+    pub label_id: Option<Uuid>,
+    pub mime_type: Option<String>,
+    pub action: String,
+    pub retain_days: i32,
+    /// Default 30
+    pub grace_days: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateRetentionPolicyRequest {
+    pub name: Option<String>,
+    pub label_id: Option<Uuid>,
+    pub mime_type: Option<String>,
+    pub action: Option<String>,
+    pub retain_days: Option<i32>,
+    pub grace_days: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+/// A document due for deletion by a policy
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct RetentionReview {
+    pub id: Uuid,
+    pub policy_id: Uuid,
+    pub policy_name: String,
+    pub document_id: Uuid,
+    pub filename: String,
+    /// `pending` until the grace period ends, `approved` to delete at the next run,
+    /// or `exempted` to keep the document
+    pub status: String,
+    pub due_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct RetentionReviewsQuery {
+    /// Only list reviews in this state (default `pending`)
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// An archival or deletion carried out by a retention policy
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct RetentionActionRecord {
+    pub id: Uuid,
+    /// None once the policy has been deleted
+    pub policy_id: Option<Uuid>,
+    pub policy_name: String,
+    pub document_id: Uuid,
+    pub filename: String,
+    pub file_hash: Option<String>,
+    /// `archive` or `delete`
+    pub action: String,
+    pub performed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct RetentionActionsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Lowercased MIME type or prefix, None when it is not of the form `type/` or
+/// `type/subtype`
+pub fn normalize_mime_pattern(pattern: &str) -> Option<String> {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    let (kind, subtype) = pattern.split_once('/')?;
+    let valid = |part: &str| part.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    if kind.is_empty() || !valid(kind) || !valid(subtype) {
+        return None;
+    }
+    Some(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_mime_pattern() {
+        assert_eq!(normalize_mime_pattern(" Application/PDF "), Some("application/pdf".to_string()));
+        assert_eq!(normalize_mime_pattern("image/"), Some("image/".to_string()));
+        assert_eq!(normalize_mime_pattern("application/vnd.ms-excel"), Some("application/vnd.ms-excel".to_string()));
+        assert_eq!(normalize_mime_pattern("pdf"), None);
+        assert_eq!(normalize_mime_pattern("/pdf"), None);
+        assert_eq!(normalize_mime_pattern("image/%"), None);
+    }
+}
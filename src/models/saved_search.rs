@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::{SearchMode, SearchRequest};
+
+/// The query and filters of a saved search, as accepted by `/api/search`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct SavedSearchQuery {
+    /// Search text; empty matches every document that passes the filters
+    #[serde(default)]
+    pub query: String,
+    pub tags: Option<Vec<String>>,
+    pub mime_types: Option<Vec<String>>,
+    /// Defaults to simple
+    pub search_mode: Option<SearchMode>,
+}
+
+impl SavedSearchQuery {
+    /// Whether the search would match every document the user can see
+    pub fn is_unrestricted(&self) -> bool {
+        self.query.trim().is_empty()
+            && self.tags.as_ref().is_none_or(|tags| tags.is_empty())
+            && self.mime_types.as_ref().is_none_or(|mime_types| mime_types.is_empty())
+    }
+
+    pub fn to_search_request(&self, limit: Option<i64>, offset: Option<i64>) -> SearchRequest {
+        SearchRequest {
+            query: self.query.clone(),
+            tags: self.tags.clone(),
+            mime_types: self.mime_types.clone(),
+            limit,
+            offset,
+            include_snippets: None,
+            snippet_length: None,
+            search_mode: self.search_mode.clone(),
+            near: None,
+            bbox: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[schema(value_type = SavedSearchQuery)]
+    pub search: sqlx::types::Json<SavedSearchQuery>,
+    /// Notify the user when a newly processed document matches
+    pub notify: bool,
+    /// When a newly processed document last matched
+    pub last_matched_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateSavedSearchRequest {
+    pub name: String,
+    pub search: SavedSearchQuery,
+    /// Defaults to false
+    pub notify: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateSavedSearchRequest {
+    pub name: Option<String>,
+    /// Replaces the whole query
+    pub search: Option<SavedSearchQuery>,
+    pub notify: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unrestricted_search() {
+        assert!(SavedSearchQuery::default().is_unrestricted());
+        assert!(SavedSearchQuery {
+            query: "  ".to_string(),
+            tags: Some(vec![]),
+            ..Default::default()
+        }
+        .is_unrestricted());
+        assert!(!SavedSearchQuery {
+            tags: Some(vec!["invoice".to_string()]),
+            ..Default::default()
+        }
+        .is_unrestricted());
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+const MAX_BRIDGE_NAME_LENGTH: usize = 100;
+const MAX_BRIDGE_INFO_LENGTH: usize = 100;
+/// Degrees a pushed page may be turned clockwise
+pub const SCAN_PAGE_ROTATIONS: [u16; 4] = [0, 90, 180, 270];
+
+/// A desktop bridge app that pushes pages from a scanner attached to its computer. The
+/// token is not part of it; it is only returned once, when the bridge collects it.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct ScanBridge {
+    pub id: Uuid,
+    /// Documents pushed by the bridge are uploaded as this user
+    pub user_id: Uuid,
+    /// What the bridge is, e.g. "Reception desk scanner"
+    pub name: String,
+    /// Start of the token, to recognise it
+    pub token_prefix: String,
+    /// Disabled bridges cannot push
+    pub enabled: bool,
+    /// Operating system the bridge reported, e.g. "windows"
+    pub platform: Option<String>,
+    /// Version of the bridge app
+    pub version: Option<String>,
+    pub pages_received: i64,
+    pub documents_received: i64,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Address the bridge last pushed from
+    pub last_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Sent by a bridge to start pairing
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct StartScanBridgePairingRequest {
+    /// Name suggested to the user, e.g. the computer's host name
+    pub name: Option<String>,
+    pub platform: Option<String>,
+    pub version: Option<String>,
+}
+
+impl StartScanBridgePairingRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.as_deref().is_some_and(|name| name.trim().chars().count() > MAX_BRIDGE_NAME_LENGTH) {
+            return Err(format!("Names are limited to {} characters", MAX_BRIDGE_NAME_LENGTH));
+        }
+        for info in [&self.platform, &self.version].into_iter().flatten() {
+            if info.chars().count() > MAX_BRIDGE_INFO_LENGTH {
+                return Err(format!("Platform and version are limited to {} characters", MAX_BRIDGE_INFO_LENGTH));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A started pairing. The bridge shows `user_code` to the user and polls for its token
+/// with `device_code` every `interval` seconds until it expires.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScanBridgePairing {
+    /// Secret of the bridge; keep it to collect the token
+    pub device_code: String,
+    /// Code the user enters in readur, e.g. "KXPM-7QRT"
+    pub user_code: String,
+    /// Page of the web app the code is entered on
+    pub verification_path: String,
+    /// Seconds until the codes expire
+    pub expires_in: i64,
+    /// Seconds to wait between polls
+    pub interval: u64,
+}
+
+/// A pairing as shown to the user approving it
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct PendingScanBridgePairing {
+    pub user_code: String,
+    pub name: String,
+    pub platform: Option<String>,
+    pub version: Option<String>,
+    /// Address the bridge asked for the pairing from
+    pub address: Option<String>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ApproveScanBridgeRequest {
+    /// The code the bridge shows, with or without the dash, in any case
+    pub user_code: String,
+    /// Replaces the name the bridge suggested
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ScanBridgeTokenRequest {
+    pub device_code: String,
+}
+
+/// A paired bridge with its token
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScanBridgeCredentials {
+    pub bridge: ScanBridge,
+    /// Bearer token for the push API; store it now, it cannot be retrieved again
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateScanBridgeRequest {
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Describes the pages of a push
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct ScanPushMetadata {
+    /// Name of the assembled PDF, defaults to `scan-<date>-<time>.pdf`
+    pub filename: Option<String>,
+    /// When the first page was scanned
+    pub scanned_at: Option<DateTime<Utc>>,
+    /// Scanner the pages came from, e.g. "Fujitsu fi-7160"
+    pub scanner: Option<String>,
+    /// Degrees to turn each page clockwise, in page order; pages without an entry are
+    /// used as they are
+    #[serde(default)]
+    pub rotations: Vec<u16>,
+    /// OCR language of the document, defaults to the user's
+    pub ocr_language: Option<String>,
+}
+
+impl ScanPushMetadata {
+    /// What is wrong with the metadata of a push of `page_count` pages
+    pub fn validate(&self, page_count: usize) -> Result<(), String> {
+        if self.rotations.len() > page_count {
+            return Err(format!("Metadata turns {} pages, but {} were sent", self.rotations.len(), page_count));
+        }
+        if let Some(index) = self.rotations.iter().position(|rotation| !SCAN_PAGE_ROTATIONS.contains(rotation)) {
+            return Err(format!("Page {}: rotation must be 0, 90, 180 or 270", index + 1));
+        }
+        if self.scanner.as_deref().is_some_and(|scanner| scanner.chars().count() > MAX_BRIDGE_INFO_LENGTH) {
+            return Err(format!("Scanner names are limited to {} characters", MAX_BRIDGE_INFO_LENGTH));
+        }
+        Ok(())
+    }
+}
+
+pub fn is_valid_bridge_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty() && name.chars().count() <= MAX_BRIDGE_NAME_LENGTH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairing_request_validate() {
+        assert!(StartScanBridgePairingRequest::default().validate().is_ok());
+        let request = StartScanBridgePairingRequest {
+            name: Some("FRONT-DESK-PC".to_string()),
+            platform: Some("windows".to_string()),
+            version: Some("1.2.0".to_string()),
+        };
+        assert!(request.validate().is_ok());
+        assert!(StartScanBridgePairingRequest { name: Some("x".repeat(101)), ..request.clone() }.validate().is_err());
+        assert!(StartScanBridgePairingRequest { version: Some("1".repeat(101)), ..request }.validate().is_err());
+    }
+
+    #[test]
+    fn test_push_metadata_validate() {
+        let metadata = ScanPushMetadata { rotations: vec![0, 180], ..Default::default() };
+        assert!(metadata.validate(2).is_ok());
+        assert!(metadata.validate(1).is_err());
+        assert!(ScanPushMetadata { rotations: vec![0, 45], ..Default::default() }.validate(2).is_err());
+    }
+
+    #[test]
+    fn test_is_valid_bridge_name() {
+        assert!(is_valid_bridge_name("Reception scanner"));
+        assert!(!is_valid_bridge_name("   "));
+        assert!(!is_valid_bridge_name(&"x".repeat(101)));
+    }
+}
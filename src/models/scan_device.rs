@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// A scanner that delivers into the FTP scan drop with credentials of its own. The
+/// password is not part of it; it is only returned when set.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct ScanDevice {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// What the device is, e.g. "2nd floor copier"
+    pub name: String,
+    /// FTP username, generated
+    pub username: String,
+    /// Disabled devices cannot sign in
+    pub enabled: bool,
+    /// Files the device delivered
+    pub documents_received: i64,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Address the device last signed in from
+    pub last_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateScanDeviceRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateScanDeviceRequest {
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// A device with its new password
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScanDeviceCredentials {
+    pub device: ScanDevice,
+    /// FTP password; store it in the device now, it cannot be retrieved again
+    pub password: String,
+    /// Host and port of the scan drop, when it is enabled
+    pub ftp_address: Option<String>,
+}
@@ -1,9 +1,12 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
 use utoipa::{ToSchema, IntoParams};
+use uuid::Uuid;
 
-use super::responses::EnhancedDocumentResponse;
+use super::responses::{EnhancedDocumentResponse, SearchSnippet};
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, IntoParams)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
 pub struct SearchRequest {
     /// Search query text (searches both document content and OCR-extracted text)
     pub query: String,
@@ -21,9 +24,14 @@ pub struct SearchRequest {
     pub snippet_length: Option<i32>,
     /// Search algorithm to use (default: simple)
     pub search_mode: Option<SearchMode>,
+    /// Only geotagged documents within a radius of a point, as `lat,lon,radius`
+    /// (radius in kilometers, or with an `m` or `km` suffix)
+    pub near: Option<String>,
+    /// Only geotagged documents inside a box, as `south,west,north,east`
+    pub bbox: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub enum SearchMode {
     /// Simple text search with basic word matching
     #[serde(rename = "simple")]
@@ -45,6 +53,63 @@ impl Default for SearchMode {
     }
 }
 
+/// A part of a document a search can match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    /// The document's content and OCR text
+    Body,
+    /// A machine translation of the document's text
+    Translation,
+    /// The text of annotations on the document's pages
+    Annotations,
+    /// Comments on the annotations
+    Comments,
+    /// Values of the document's custom fields
+    CustomFields,
+}
+
+impl SearchField {
+    /// Fields indexed apart from the body, in `document_field_search`
+    pub const INDEXED: [SearchField; 3] = [SearchField::Annotations, SearchField::Comments, SearchField::CustomFields];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchField::Body => "body",
+            SearchField::Translation => "translation",
+            SearchField::Annotations => "annotations",
+            SearchField::Comments => "comments",
+            SearchField::CustomFields => "custom_fields",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            SearchField::Body,
+            SearchField::Translation,
+            SearchField::Annotations,
+            SearchField::Comments,
+            SearchField::CustomFields,
+        ]
+        .into_iter()
+        .find(|field| field.as_str() == value)
+    }
+
+    /// What a match in the field counts for in the ranking, next to 1 for the body.
+    /// Notes and field values are a few words where the body has thousands, so a match
+    /// in them would otherwise rank far below one in a long text. Translations find
+    /// documents but do not rank them.
+    pub fn boost(&self) -> f32 {
+        match self {
+            SearchField::Body => 1.0,
+            SearchField::Translation => 0.0,
+            SearchField::Annotations => 2.0,
+            SearchField::Comments => 1.5,
+            SearchField::CustomFields => 2.0,
+        }
+    }
+}
+
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SearchResponse {
@@ -56,6 +121,30 @@ pub struct SearchResponse {
     pub query_time_ms: u64,
     /// Search suggestions for query improvement
     pub suggestions: Vec<String>,
+    /// Counts of all matching documents per filter value, when requested with `include_facets`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<SearchResultFacets>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SearchFacetsParams {
+    /// Whether to count the matching documents by label, MIME type, source, correspondent and year (default: false)
+    pub include_facets: Option<bool>,
+}
+
+/// Counts of the documents matching a search, to offer drill-down filters
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct SearchResultFacets {
+    /// Tags and labels, most frequent first
+    pub labels: Vec<FacetItem>,
+    /// MIME types, most frequent first
+    pub mime_types: Vec<FacetItem>,
+    /// Names of the sources documents were synced from, or the upload type
+    pub sources: Vec<FacetItem>,
+    /// Correspondents of documents imported from Paperless
+    pub correspondents: Vec<FacetItem>,
+    /// Years the documents were added, newest first
+    pub years: Vec<FacetItem>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -72,4 +161,58 @@ pub struct SearchFacetsResponse {
     pub mime_types: Vec<FacetItem>,
     /// Tag facets with counts
     pub tags: Vec<FacetItem>,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DocumentSearchQuery {
+    /// Text to find; words may be separated by any whitespace, including line and page breaks
+    pub q: String,
+    /// Maximum number of matches to return (default: 100, max: 1000)
+    pub limit: Option<i64>,
+    /// Number of matches to skip (default: 0)
+    pub offset: Option<i64>,
+    /// Length of the snippet around each match in characters (default: 120)
+    pub snippet_length: Option<i32>,
+}
+
+/// Occurrences of a phrase within one document, in reading order
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DocumentSearchResponse {
+    pub document_id: Uuid,
+    pub query: String,
+    /// Number of occurrences in the whole document
+    pub total: i64,
+    /// Each occurrence with its page, offsets into the text and a snippet
+    pub matches: Vec<SearchSnippet>,
+}
+
+/// Progress of indexing documents into the external search engine
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ExternalSearchState {
+    pub engine: String,
+    pub index_name: String,
+    /// Position in the event log the index is up to date with
+    pub last_event_at: Option<DateTime<Utc>>,
+    pub last_event_id: Option<Uuid>,
+    /// `never`, `running`, `completed` or `failed`
+    pub resync_status: String,
+    pub resync_started_at: Option<DateTime<Utc>>,
+    pub resync_completed_at: Option<DateTime<Utc>>,
+    /// Documents sent by the last full re-sync
+    pub resync_documents: i64,
+    pub last_error: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExternalSearchStatus {
+    pub enabled: bool,
+    /// `meilisearch` or `typesense`
+    pub engine: Option<String>,
+    pub index: Option<String>,
+    /// Whether searches are answered by the engine
+    pub query_enabled: bool,
+    /// Document fields and the names they are indexed under, as `field:name`
+    pub fields: Vec<String>,
+    pub state: Option<ExternalSearchState>,
+}
@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Languages documents are indexed in: the Postgres language names of the
+/// `readur_<language>` text search configurations. Documents take their owner's OCR
+/// language; languages without a stemmer are indexed as `simple`.
+pub const SEARCH_LANGUAGES: [&str; 16] = [
+    "english", "german", "french", "spanish", "italian", "portuguese", "dutch", "danish", "norwegian", "swedish",
+    "finnish", "hungarian", "romanian", "russian", "turkish", "simple",
+];
+
+/// Most dictionaries one language may chain
+pub const MAX_SEARCH_DICTIONARIES: usize = 8;
+/// Most synonyms one term may have
+pub const MAX_SYNONYMS_PER_TERM: usize = 32;
+
+/// How words of a language are turned into the lexemes documents are indexed and
+/// searched by
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SearchAnalyzer {
+    pub language: String,
+    /// Whether accents are folded before lookup, so "resume" finds "résumé"
+    pub fold_accents: bool,
+    /// Text search dictionaries a word is looked up in, in order; the first that
+    /// recognizes it decides its lexemes
+    pub dictionaries: Vec<String>,
+    /// None for the built-in analyzer
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl SearchAnalyzer {
+    /// The analyzer a language has until an admin changes it
+    pub fn default_for(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            fold_accents: true,
+            dictionaries: vec![default_dictionary(language)],
+            updated_by: None,
+            updated_at: None,
+        }
+    }
+}
+
+/// The snowball stemmer of a language, or `simple` for the fallback configuration
+pub fn default_dictionary(language: &str) -> String {
+    if language == "simple" {
+        "simple".to_string()
+    } else {
+        format!("{}_stem", language)
+    }
+}
+
+/// Whether `name` can be a text search dictionary name: it is passed to the database
+/// quoted, but odd names are refused early with a clear reason
+fn is_valid_dictionary_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 63
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSearchAnalyzerRequest {
+    pub fold_accents: bool,
+    /// Dictionaries installed in the database, e.g. `german_stem`, an ispell or
+    /// hunspell dictionary, or `simple`
+    pub dictionaries: Vec<String>,
+    /// Rebuild the index of the language's documents right away (default: true)
+    #[serde(default = "default_rebuild")]
+    pub rebuild: bool,
+}
+
+fn default_rebuild() -> bool {
+    true
+}
+
+impl UpdateSearchAnalyzerRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.dictionaries.is_empty() {
+            return Err("At least one dictionary is required".to_string());
+        }
+        if self.dictionaries.len() > MAX_SEARCH_DICTIONARIES {
+            return Err(format!("At most {} dictionaries can be chained", MAX_SEARCH_DICTIONARIES));
+        }
+        if let Some(name) = self.dictionaries.iter().find(|name| !is_valid_dictionary_name(name)) {
+            return Err(format!("Invalid dictionary name: {}", name));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchAnalyzerUpdateResponse {
+    pub analyzer: SearchAnalyzer,
+    /// Rebuild of the language's documents, when one was started
+    pub rebuild: Option<SearchIndexRebuild>,
+}
+
+/// Words a search for `term` also finds
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SearchSynonym {
+    pub id: Uuid,
+    pub language: String,
+    pub term: String,
+    pub synonyms: Vec<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateSearchSynonymRequest {
+    /// One of the search languages
+    pub language: String,
+    pub term: String,
+    pub synonyms: Vec<String>,
+}
+
+impl CreateSearchSynonymRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if !SEARCH_LANGUAGES.contains(&self.language.as_str()) {
+            return Err(format!("Unknown search language: {}", self.language));
+        }
+        if self.term.trim().is_empty() {
+            return Err("Term is required".to_string());
+        }
+        if self.synonyms.iter().all(|synonym| synonym.trim().is_empty()) {
+            return Err("At least one synonym is required".to_string());
+        }
+        if self.synonyms.len() > MAX_SYNONYMS_PER_TERM {
+            return Err(format!("At most {} synonyms per term", MAX_SYNONYMS_PER_TERM));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct SearchSynonymsQuery {
+    /// Only synonyms of this language
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct SearchReindexQuery {
+    /// Only documents indexed in this language (default: every document)
+    pub language: Option<String>,
+}
+
+/// A rebuild of the full-text index and how far it got
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SearchIndexRebuild {
+    pub id: Uuid,
+    /// None when every document is rebuilt
+    pub language: Option<String>,
+    /// `running`, `completed` or `failed`
+    pub status: String,
+    pub total_documents: i64,
+    pub processed_documents: i64,
+    #[serde(skip)]
+    pub last_document_id: Option<Uuid>,
+    pub error: Option<String>,
+    pub started_by: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyzer_validation() {
+        let request = UpdateSearchAnalyzerRequest {
+            fold_accents: true,
+            dictionaries: vec!["german_hunspell".to_string(), "german_stem".to_string()],
+            rebuild: true,
+        };
+        assert!(request.validate().is_ok());
+        assert!(UpdateSearchAnalyzerRequest { dictionaries: vec![], ..request.clone() }.validate().is_err());
+        assert!(UpdateSearchAnalyzerRequest { dictionaries: vec!["german_stem; DROP".to_string()], ..request }
+            .validate()
+            .is_err());
+
+        assert_eq!(SearchAnalyzer::default_for("german").dictionaries, vec!["german_stem"]);
+        assert_eq!(SearchAnalyzer::default_for("simple").dictionaries, vec!["simple"]);
+    }
+}
@@ -0,0 +1,122 @@
+use axum::http::Method;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// A login session. Its JWT stops working once the session is revoked or expires.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct UserSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// How the user logged in, e.g. `password`, `password+totp`, `passkey` or `oidc`
+    pub method: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Whether this is the session making the request
+    #[sqlx(default)]
+    pub current: bool,
+}
+
+/// Session method of impersonation sessions
+pub const IMPERSONATION_METHOD: &str = "impersonation";
+/// Lifetime of an impersonation token unless the admin asks for another
+pub const IMPERSONATION_DEFAULT_MINUTES: i64 = 30;
+pub const IMPERSONATION_MAX_MINUTES: i64 = 240;
+
+/// Paths an impersonation token may only read, so the admin cannot change the user's
+/// credentials or account, or hand themselves lasting access as the user
+const IMPERSONATION_READ_ONLY_PATHS: [&str; 2] = ["/api/auth/", "/api/users/"];
+
+/// An admin acting as a user, carried in the impersonation token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Impersonator {
+    pub id: Uuid,
+    pub username: String,
+    /// Whether the token may only read
+    pub read_only: bool,
+}
+
+impl Impersonator {
+    /// Whether the token may make a request. Logging out is always allowed, to end
+    /// the impersonation.
+    pub fn allows(&self, method: &Method, path: &str) -> bool {
+        let reading = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+        if path == "/api/auth/logout" {
+            return true;
+        }
+        if IMPERSONATION_READ_ONLY_PATHS.iter().any(|prefix| path.starts_with(prefix)) {
+            return reading;
+        }
+        reading || !self.read_only
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ImpersonateRequest {
+    /// Why the user is impersonated, e.g. a support ticket; recorded in the audit log
+    pub reason: String,
+    /// Lifetime of the token (default 30, at most 240)
+    pub minutes: Option<i64>,
+    /// Whether the token may only read (default true)
+    pub read_only: Option<bool>,
+}
+
+impl ImpersonateRequest {
+    /// The token's lifetime in minutes
+    pub fn validate(&self) -> Result<i64, String> {
+        if self.reason.trim().is_empty() {
+            return Err("a reason is required".to_string());
+        }
+        match self.minutes.unwrap_or(IMPERSONATION_DEFAULT_MINUTES) {
+            minutes if (1..=IMPERSONATION_MAX_MINUTES).contains(&minutes) => Ok(minutes),
+            _ => Err(format!("minutes must be between 1 and {}", IMPERSONATION_MAX_MINUTES)),
+        }
+    }
+}
+
+/// A token acting as the user until it expires or its session is revoked
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ImpersonationResponse {
+    pub token: String,
+    pub user_id: Uuid,
+    pub username: String,
+    /// The session the token belongs to; it shows in the user's sessions
+    pub session_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub read_only: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impersonator_allows() {
+        let read_only = Impersonator { id: Uuid::new_v4(), username: "admin".to_string(), read_only: true };
+        assert!(read_only.allows(&Method::GET, "/api/documents"));
+        assert!(!read_only.allows(&Method::POST, "/api/documents"));
+        assert!(read_only.allows(&Method::POST, "/api/auth/logout"));
+
+        let writing = Impersonator { read_only: false, ..read_only };
+        assert!(writing.allows(&Method::POST, "/api/documents"));
+        assert!(writing.allows(&Method::GET, "/api/users/me/tokens"));
+        assert!(!writing.allows(&Method::POST, "/api/users/me/tokens"));
+        assert!(!writing.allows(&Method::PUT, "/api/users/550e8400-e29b-41d4-a716-446655440000"));
+        assert!(!writing.allows(&Method::POST, "/api/auth/2fa/enable"));
+    }
+
+    #[test]
+    fn test_impersonate_request_validate() {
+        let request = |reason: &str, minutes| ImpersonateRequest { reason: reason.to_string(), minutes, read_only: None };
+        assert_eq!(request("Ticket 1234", None).validate(), Ok(IMPERSONATION_DEFAULT_MINUTES));
+        assert_eq!(request("Ticket 1234", Some(5)).validate(), Ok(5));
+        assert!(request("  ", None).validate().is_err());
+        assert!(request("Ticket 1234", Some(0)).validate().is_err());
+        assert!(request("Ticket 1234", Some(IMPERSONATION_MAX_MINUTES + 1)).validate().is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// A public link to a document. The token is only returned when the link is created.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct ShareLink {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub document_filename: String,
+    pub created_by: Option<Uuid>,
+    pub created_by_username: Option<String>,
+    #[serde(skip)]
+    pub password_hash: Option<String>,
+    pub has_password: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_downloads: Option<i32>,
+    pub download_count: i32,
+    pub watermark: bool,
+    /// Text stamped on PDF downloads when `watermark` is set
+    pub watermark_text: Option<String>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_accessed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ShareLink {
+    /// Whether the link can still be used to download the document
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none()
+            && self.expires_at.is_none_or(|expires_at| expires_at > now)
+            && self.max_downloads.is_none_or(|max| self.download_count < max)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateShareLinkRequest {
+    /// The link stops working after this many hours; never when omitted
+    pub expires_in_hours: Option<i64>,
+    /// Visitors must enter this password to download
+    pub password: Option<String>,
+    /// The link stops working after this many downloads
+    pub max_downloads: Option<i32>,
+    /// Stamp every page of PDF downloads, default false
+    pub watermark: Option<bool>,
+    /// Watermark text, e.g. the recipient; defaults to "Shared via Readur" with the link
+    /// creation date
+    pub watermark_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CreateShareLinkResponse {
+    pub link: ShareLink,
+    /// Secret part of the link; store it now, it cannot be retrieved again
+    pub token: String,
+    /// Path of the public download page for the token
+    pub url: String,
+}
+
+/// What a visitor of a public link sees before downloading. File details are only
+/// filled in once the password of a protected link is given.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PublicShareLinkInfo {
+    pub filename: Option<String>,
+    pub mime_type: Option<String>,
+    pub file_size: Option<i64>,
+    pub requires_password: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub downloads_remaining: Option<i32>,
+    pub watermarked: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ShareLinkPasswordRequest {
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct ShareLinksQuery {
+    /// Include revoked, expired and used up links, default false
+    pub include_inactive: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn link() -> ShareLink {
+        ShareLink {
+            id: Uuid::new_v4(),
+            document_id: Uuid::new_v4(),
+            document_filename: "report.pdf".to_string(),
+            created_by: None,
+            created_by_username: None,
+            password_hash: None,
+            has_password: false,
+            expires_at: None,
+            max_downloads: None,
+            download_count: 0,
+            watermark: false,
+            watermark_text: None,
+            revoked_at: None,
+            last_accessed_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_share_link_is_active() {
+        let now = Utc::now();
+        assert!(link().is_active(now));
+
+        let expired = ShareLink { expires_at: Some(now - Duration::minutes(1)), ..link() };
+        assert!(!expired.is_active(now));
+
+        let used_up = ShareLink { max_downloads: Some(2), download_count: 2, ..link() };
+        assert!(!used_up.is_active(now));
+
+        let revoked = ShareLink { revoked_at: Some(now), ..link() };
+        assert!(!revoked.is_active(now));
+    }
+
+    #[test]
+    fn test_password_hash_is_not_serialized() {
+        let with_password = ShareLink { password_hash: Some("$2b$12$secret".to_string()), has_password: true, ..link() };
+        let json = serde_json::to_string(&with_password).unwrap();
+        assert!(!json.contains("secret"));
+        assert!(json.contains("\"has_password\":true"));
+    }
+}
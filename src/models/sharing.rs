@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::fmt;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// What a share lets its grantee do. Each level includes the ones before it, so they
+/// compare in that order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "share_permission", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SharePermission {
+    /// Find the document in search, open and download it
+    View,
+    /// Also rename it and change its labels
+    Edit,
+    /// Also delete it
+    Delete,
+}
+
+impl fmt::Display for SharePermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SharePermission::View => write!(f, "view"),
+            SharePermission::Edit => write!(f, "edit"),
+            SharePermission::Delete => write!(f, "delete"),
+        }
+    }
+}
+
+/// A team of users that documents and labels can be shared with
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub member_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateUserGroupRequest {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateUserGroupRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserGroupMember {
+    pub user_id: Uuid,
+    pub username: String,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AddGroupMemberRequest {
+    pub user_id: Uuid,
+}
+
+/// A document, label or collection shared with a user or a group
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Share {
+    pub id: Uuid,
+    /// The shared document, label or collection
+    pub resource_id: Uuid,
+    /// Set when shared with a user
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    /// Set when shared with a group
+    pub group_id: Option<Uuid>,
+    pub group_name: Option<String>,
+    pub permission: SharePermission,
+    pub granted_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Share with exactly one of a user or a group. Sharing again with the same grantee
+/// replaces the permission.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateShareRequest {
+    pub user_id: Option<Uuid>,
+    pub group_id: Option<Uuid>,
+    /// Default `view`
+    pub permission: Option<SharePermission>,
+}
+
+/// A document another user shared with the current user
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SharedDocument {
+    pub id: Uuid,
+    pub original_filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    pub owner_id: Uuid,
+    pub owner_username: String,
+    /// The highest permission any share grants the current user
+    pub permission: SharePermission,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct SharedDocumentsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_permission_levels() {
+        assert!(SharePermission::View < SharePermission::Edit);
+        assert!(SharePermission::Edit < SharePermission::Delete);
+        assert_eq!(serde_json::to_string(&SharePermission::Edit).unwrap(), "\"edit\"");
+        assert_eq!(serde_json::from_str::<SharePermission>("\"delete\"").unwrap(), SharePermission::Delete);
+    }
+}
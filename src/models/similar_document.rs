@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct SimilarDocumentsQuery {
+    /// Bits per page hash that may differ, 0-32 (default 10)
+    pub max_distance: Option<u32>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SimilarDocument {
+    pub id: Uuid,
+    pub original_filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    pub file_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Largest page hash distance to the first document of the group
+    pub distance: u32,
+}
+
+/// Documents that look like scans of the same paper, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SimilarDocumentGroup {
+    pub documents: Vec<SimilarDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SimilarDocumentsResponse {
+    pub groups: Vec<SimilarDocumentGroup>,
+    pub total_groups: i64,
+    /// PDFs and images not hashed yet, see `POST /api/documents/duplicates/similar/scan`
+    pub unhashed_documents: i64,
+}
+
+/// Mark documents of a group as not being duplicates of each other
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct DismissSimilarDocumentsRequest {
+    pub document_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct MergeDuplicatesRequest {
+    /// Document to keep
+    pub keep_id: Uuid,
+    /// Documents whose labels, tags and physical location move to the kept one before
+    /// they are deleted
+    pub duplicate_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MergeDuplicatesResponse {
+    pub kept_id: Uuid,
+    pub deleted: i64,
+    pub labels_added: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct RelatedDocumentsQuery {
+    /// Maximum number of documents (default 10, max 50)
+    pub limit: Option<i64>,
+}
+
+/// A document whose text resembles another one's
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RelatedDocument {
+    pub id: Uuid,
+    pub original_filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    /// Text similarity from 0 to 1
+    pub score: f32,
+    /// Distinctive terms of the source document that this one contains too
+    pub shared_terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RelatedDocumentsResponse {
+    pub document_id: Uuid,
+    /// The source document's most distinctive terms (stemmed, tf-idf weighted) the
+    /// ranking is based on
+    pub terms: Vec<String>,
+    /// Most similar first
+    pub documents: Vec<RelatedDocument>,
+}
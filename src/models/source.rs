@@ -1,8 +1,8 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use serde_json;
 
 use super::responses::DocumentResponse;
@@ -15,6 +15,14 @@ pub enum SourceType {
     LocalFolder,
     #[serde(rename = "s3")]
     S3,
+    #[serde(rename = "imap")]
+    Imap,
+    #[serde(rename = "dropbox")]
+    Dropbox,
+    #[serde(rename = "onedrive")]
+    OneDrive,
+    #[serde(rename = "sftp")]
+    Sftp,
 }
 
 impl std::fmt::Display for SourceType {
@@ -23,6 +31,10 @@ impl std::fmt::Display for SourceType {
             SourceType::WebDAV => write!(f, "webdav"),
             SourceType::LocalFolder => write!(f, "local_folder"),
             SourceType::S3 => write!(f, "s3"),
+            SourceType::Imap => write!(f, "imap"),
+            SourceType::Dropbox => write!(f, "dropbox"),
+            SourceType::OneDrive => write!(f, "onedrive"),
+            SourceType::Sftp => write!(f, "sftp"),
         }
     }
 }
@@ -35,6 +47,10 @@ impl TryFrom<String> for SourceType {
             "webdav" => Ok(SourceType::WebDAV),
             "local_folder" => Ok(SourceType::LocalFolder),
             "s3" => Ok(SourceType::S3),
+            "imap" => Ok(SourceType::Imap),
+            "dropbox" => Ok(SourceType::Dropbox),
+            "onedrive" => Ok(SourceType::OneDrive),
+            "sftp" => Ok(SourceType::Sftp),
             _ => Err(format!("Invalid source type: {}", value)),
         }
     }
@@ -134,6 +150,12 @@ pub struct SourceResponse {
     pub validation_score: Option<i32>,
     #[serde(default)]
     pub validation_issues: Option<String>,
+    /// When the next scheduled sync starts; null when the source does not sync on its own
+    #[serde(default)]
+    pub next_sync_at: Option<DateTime<Utc>>,
+    /// How long the last sync ran
+    #[serde(default)]
+    pub last_sync_duration_ms: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -168,6 +190,21 @@ pub struct WebDAVSourceConfig {
     pub auto_sync: bool,
     pub sync_interval_minutes: i32,
     pub server_type: Option<String>,
+    #[serde(default)]
+    pub sync_direction: WebDAVSyncDirection,
+}
+
+/// Whether changes made in Readur are written back to the WebDAV server
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum WebDAVSyncDirection {
+    /// Only download from the server
+    #[default]
+    #[serde(rename = "pull")]
+    Pull,
+    /// Also delete and rename files on the server when their documents are deleted
+    /// or renamed in Readur, unless the file changed on the server since it was synced
+    #[serde(rename = "bidirectional")]
+    Bidirectional,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -178,6 +215,25 @@ pub struct LocalFolderSourceConfig {
     pub sync_interval_minutes: i32,
     pub recursive: bool,
     pub follow_symlinks: bool,
+    /// How new files are noticed between scheduled syncs
+    #[serde(default)]
+    pub watch_mode: LocalFolderWatchMode,
+}
+
+/// How a local folder source notices files dropped into its folders. Scheduled syncs
+/// run in every mode and pick up anything a watcher missed.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalFolderWatchMode {
+    /// Filesystem notifications, or polling for folders on network mounts
+    #[default]
+    Auto,
+    /// Filesystem notifications (inotify, FSEvents, ReadDirectoryChangesW)
+    Notify,
+    /// Compare the folders' contents every `LOCAL_FOLDER_POLL_INTERVAL_SECONDS`
+    Polling,
+    /// Only scheduled syncs
+    Off,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -192,6 +248,417 @@ pub struct S3SourceConfig {
     pub file_extensions: Vec<String>,
     pub auto_sync: bool,
     pub sync_interval_minutes: i32,
+    /// Ingest new objects from bucket notifications as well as by polling
+    #[serde(default)]
+    pub notifications: Option<S3NotificationConfig>,
+}
+
+/// Where an S3 source receives the bucket's object-created notifications
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct S3NotificationConfig {
+    /// SQS queue the bucket publishes to (directly or through SNS)
+    pub sqs_queue_url: Option<String>,
+    /// Endpoint of an SQS-compatible service; AWS is used when empty
+    pub sqs_endpoint_url: Option<String>,
+    /// Secret the bucket's webhook target sends as a bearer token to
+    /// `POST /api/sources/{id}/s3-events`
+    pub webhook_secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImapSourceConfig {
+    pub server: String,
+    /// IMAPS port, the connection is always TLS
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    #[serde(default = "default_imap_mailbox")]
+    pub mailbox: String,
+    /// Only messages from senders containing one of these strings are processed; empty matches all
+    #[serde(default)]
+    pub from_filters: Vec<String>,
+    /// Only messages whose subject contains this string are processed
+    pub subject_filter: Option<String>,
+    /// Attachment extensions to ingest; empty ingests every attachment
+    #[serde(default)]
+    pub file_extensions: Vec<String>,
+    #[serde(default)]
+    pub label_rules: Vec<ImapLabelRule>,
+    #[serde(default)]
+    pub processed_action: ImapProcessedAction,
+    /// Destination mailbox for `move`
+    pub processed_mailbox: Option<String>,
+    pub auto_sync: bool,
+    pub sync_interval_minutes: i32,
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_imap_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+/// Label applied to documents from messages matching every condition given (case-insensitive)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImapLabelRule {
+    pub from_contains: Option<String>,
+    pub subject_contains: Option<String>,
+    pub label: String,
+}
+
+/// What happens to a message once its attachments are ingested
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum ImapProcessedAction {
+    /// Flag the message as seen; only unseen messages are polled
+    #[default]
+    #[serde(rename = "mark_seen")]
+    MarkSeen,
+    /// Move the message to `processed_mailbox`
+    #[serde(rename = "move")]
+    Move,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DropboxSourceConfig {
+    /// App key and secret of the Dropbox app the refresh token was issued to
+    pub app_key: String,
+    pub app_secret: String,
+    /// Long-lived refresh token from the app's offline-access authorization
+    pub refresh_token: String,
+    /// Folder to sync, e.g. `/Scans`; the whole Dropbox when empty
+    #[serde(default)]
+    pub root_path: String,
+    /// Glob patterns relative to `root_path` (e.g. `invoices/**/*.pdf`); empty includes everything
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    pub auto_sync: bool,
+    pub sync_interval_minutes: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OneDriveSourceConfig {
+    /// Azure AD tenant; `common` accepts personal and work accounts
+    #[serde(default = "default_onedrive_tenant")]
+    pub tenant_id: String,
+    pub client_id: String,
+    /// Only needed for confidential (web) app registrations
+    pub client_secret: Option<String>,
+    pub refresh_token: String,
+    /// SharePoint document library or other drive; the signed-in user's OneDrive when empty
+    pub drive_id: Option<String>,
+    /// Folder to sync, e.g. `/Shared Documents/Scans`; the whole drive when empty
+    #[serde(default)]
+    pub root_path: String,
+    /// Glob patterns relative to `root_path`; empty includes everything
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    pub auto_sync: bool,
+    pub sync_interval_minutes: i32,
+}
+
+fn default_onedrive_tenant() -> String {
+    "common".to_string()
+}
+
+/// A directory on an SFTP, FTPS or FTP server that scanners upload into
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SftpSourceConfig {
+    #[serde(default)]
+    pub protocol: FileTransferProtocol,
+    pub host: String,
+    /// Defaults to 22 for SFTP and 21 for FTPS and FTP
+    pub port: Option<u16>,
+    pub username: String,
+    pub password: Option<String>,
+    /// PEM or OpenSSH private key, SFTP only
+    pub private_key: Option<String>,
+    pub private_key_passphrase: Option<String>,
+    /// Expected SSH host key, as printed by `ssh-keygen -lf` (e.g. `SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8`)
+    pub host_key_fingerprint: Option<String>,
+    /// Skip host key verification; only for trusted networks
+    #[serde(default)]
+    pub accept_any_host_key: bool,
+    /// Accept self-signed or otherwise invalid FTPS certificates
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    pub remote_path: String,
+    #[serde(default)]
+    pub recursive: bool,
+    /// Extensions to ingest; empty ingests every file
+    #[serde(default)]
+    pub file_extensions: Vec<String>,
+    /// Files modified more recently than this are left for the next poll, so uploads
+    /// still in progress are not picked up
+    #[serde(default = "default_settle_seconds")]
+    pub settle_seconds: u32,
+    #[serde(default)]
+    pub processed_action: RemoteProcessedAction,
+    /// Destination directory for `move`, created if missing
+    pub processed_path: Option<String>,
+    pub auto_sync: bool,
+    pub sync_interval_minutes: i32,
+}
+
+fn default_settle_seconds() -> u32 {
+    60
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum FileTransferProtocol {
+    #[default]
+    #[serde(rename = "sftp")]
+    Sftp,
+    /// FTP upgraded to TLS with `AUTH TLS` (explicit FTPS)
+    #[serde(rename = "ftps")]
+    Ftps,
+    /// Unencrypted FTP, for scanners that support nothing else
+    #[serde(rename = "ftp")]
+    Ftp,
+}
+
+/// What happens to a remote file once it is ingested
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum RemoteProcessedAction {
+    /// Leave the file; it is downloaded again only when its size or modification time changes
+    #[default]
+    #[serde(rename = "keep")]
+    Keep,
+    #[serde(rename = "delete")]
+    Delete,
+    /// Move the file to `processed_path`
+    #[serde(rename = "move")]
+    Move,
+}
+
+/// A file or message to run a source's filter and label rules against
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct SimulateSourceRulesRequest {
+    /// Use an existing document's source path and email metadata as the sample
+    pub document_id: Option<Uuid>,
+    /// Path as the source reports it, e.g. `/Scans/2024/invoice.pdf`
+    pub path: Option<String>,
+    /// Sender and subject, for IMAP sources
+    pub from: Option<String>,
+    pub subject: Option<String>,
+    /// Unsaved configuration to evaluate instead of the source's current one
+    pub config: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SourceRuleDecision {
+    /// Setting the rule comes from, e.g. `include_patterns[0]` or `label_rules[1]`
+    pub rule: String,
+    pub matched: bool,
+    pub detail: String,
+}
+
+/// What a sync would do with a sample, rule by rule. Nothing is stored.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SourceRuleSimulation {
+    pub source_type: SourceType,
+    pub path: String,
+    pub decisions: Vec<SourceRuleDecision>,
+    pub would_ingest: bool,
+    /// Labels the ingested document would get
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPreviewAction {
+    Ingest,
+    Skip,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SyncPreviewEntry {
+    pub action: SyncPreviewAction,
+    pub path: String,
+    pub size: Option<i64>,
+    pub reason: String,
+    /// Document the file was already ingested as
+    pub existing_document_id: Option<Uuid>,
+}
+
+/// What a sync would ingest, skip and delete, from a walk of the source. Nothing is
+/// downloaded or changed.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SourceSyncPreview {
+    pub source_id: Uuid,
+    pub source_type: SourceType,
+    pub files_discovered: i64,
+    pub would_ingest: i64,
+    pub would_ingest_bytes: i64,
+    pub would_skip: i64,
+    pub would_delete: i64,
+    /// The first entries up to the requested limit; the counts cover every file
+    pub entries: Vec<SyncPreviewEntry>,
+    pub truncated: bool,
+    /// Folders that could not be listed, and other reasons the sync may differ
+    pub warnings: Vec<String>,
+}
+
+/// Incremental sync state of a cloud drive source: the provider's delta cursor and
+/// the current OAuth tokens, which the provider may rotate on refresh
+#[derive(Debug, Clone, FromRow)]
+pub struct SourceSyncState {
+    pub source_id: Uuid,
+    pub delta_cursor: Option<String>,
+    pub access_token: Option<String>,
+    pub access_token_expires_at: Option<DateTime<Utc>>,
+    pub refresh_token: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// When a source syncs on its own, and how long its last sync took
+#[derive(Debug, Clone, FromRow)]
+pub struct SourceSchedule {
+    pub source_id: Uuid,
+    pub cron_expression: Option<String>,
+    pub quiet_hours_start: Option<NaiveTime>,
+    pub quiet_hours_end: Option<NaiveTime>,
+    pub max_runtime_minutes: Option<i32>,
+    pub last_sync_started_at: Option<DateTime<Utc>>,
+    pub last_sync_duration_ms: Option<i64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Replaces the schedule of a source; fields left out are cleared
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSourceScheduleRequest {
+    /// Five-field cron expression in UTC, e.g. `0 2 * * *`. Without one the source syncs
+    /// every `sync_interval_minutes` of its config.
+    pub cron_expression: Option<String>,
+    /// Start of the hours no scheduled sync starts in, `HH:MM` in UTC
+    pub quiet_hours_start: Option<String>,
+    /// End of the quiet hours, `HH:MM` in UTC; before the start when they span midnight
+    pub quiet_hours_end: Option<String>,
+    /// Syncs running longer are stopped
+    pub max_runtime_minutes: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SourceScheduleResponse {
+    pub cron_expression: Option<String>,
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    pub max_runtime_minutes: Option<i32>,
+    /// When the next scheduled sync starts; null when the source does not sync on its own
+    pub next_sync_at: Option<DateTime<Utc>>,
+    pub last_sync_started_at: Option<DateTime<Utc>>,
+    /// How long the last sync ran, whether it succeeded or not
+    pub last_sync_duration_ms: Option<i64>,
+}
+
+/// Templates that read metadata from the names of a source's files, tried in order
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SourceFilenameTemplates {
+    pub source_id: Uuid,
+    /// E.g. `{date}_{correspondent}_{title}.pdf`
+    pub templates: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Replaces the filename templates of a source; an empty list removes them
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateFilenameTemplatesRequest {
+    pub templates: Vec<String>,
+}
+
+/// Sample file names to parse, with the source's templates or with templates to try out
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TestFilenameTemplatesRequest {
+    pub filenames: Vec<String>,
+    /// Defaults to the source's saved templates
+    pub templates: Option<Vec<String>>,
+}
+
+/// Metadata read from a file name by one of its source's filename templates
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ParsedFilename {
+    /// The template that matched
+    pub template: String,
+    /// Becomes the document's original creation date
+    pub date: Option<NaiveDate>,
+    pub correspondent: Option<String>,
+    /// Becomes the document's name, keeping the file's extension
+    pub title: Option<String>,
+    pub document_type: Option<String>,
+    /// Labels for the document
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FilenameParsePreview {
+    pub filename: String,
+    /// Null when no template matches
+    pub parsed: Option<ParsedFilename>,
+}
+
+/// One sync of a source and what it did to the files it saw
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SourceSyncRun {
+    pub id: Uuid,
+    pub source_id: Uuid,
+    /// `running`, `succeeded`, `failed`, `cancelled`, or `interrupted` by a server restart
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Files stored as new documents
+    pub files_added: i32,
+    /// Changed files stored as new versions of their documents
+    pub files_updated: i32,
+    /// Files already in Readur, from this source or as duplicates of other documents
+    pub files_skipped: i32,
+    pub files_failed: i32,
+    /// Why the run failed as a whole
+    pub error: Option<String>,
+    /// Why files failed, for the first failures of the run
+    #[schema(value_type = Vec<SyncRunFileError>)]
+    pub file_errors: sqlx::types::Json<Vec<SyncRunFileError>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SyncRunFileError {
+    /// Path of the file in the source, or the folder that could not be listed
+    pub path: String,
+    pub error: String,
+}
+
+/// How a sync run ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceSyncRunOutcome {
+    pub status: &'static str,
+    pub error: Option<String>,
+    pub files_added: i32,
+    pub files_updated: i32,
+    pub files_skipped: i32,
+    pub files_failed: i32,
+    pub file_errors: Vec<SyncRunFileError>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct SourceListQuery {
+    /// Sources per page, at most 200; without it and `cursor` all sources are returned
+    pub limit: Option<i64>,
+    /// Cursor of the next page, from the `Link` header of the previous one
+    pub cursor: Option<String>,
+    /// Send `X-Total-Count` for paged requests, default true
+    pub include_total: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct SourceSyncRunListQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
 // WebDAV-related structs
@@ -294,6 +761,27 @@ pub struct CreateWebDAVFile {
     pub sync_error: Option<String>,
 }
 
+/// A delete or rename made in Readur, waiting to be applied to a bidirectional WebDAV source
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WebDAVOutgoingChange {
+    pub id: Uuid,
+    pub source_id: Uuid,
+    pub document_id: Uuid,
+    /// `delete` or `rename`
+    pub operation: String,
+    pub remote_path: String,
+    /// Destination of a rename
+    pub new_remote_path: Option<String>,
+    /// ETag the file had when it was synced; the change is not applied if the server's differs
+    pub expected_etag: Option<String>,
+    /// `pending`, `applied`, `conflict` or `failed`
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct WebDAVDirectory {
     pub id: Uuid,
@@ -572,6 +1060,8 @@ impl From<Source> for SourceResponse {
             last_validation_at: source.last_validation_at,
             validation_score: source.validation_score,
             validation_issues: source.validation_issues,
+            next_sync_at: None,
+            last_sync_duration_ms: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,292 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Days covered when a request gives no start date
+pub const DEFAULT_STATISTICS_DAYS: i64 = 30;
+/// Most buckets one series may have, a little over a year of days
+pub const MAX_STATISTICS_BUCKETS: i64 = 400;
+pub const DEFAULT_STATISTICS_TOP: i64 = 10;
+pub const MAX_STATISTICS_TOP: i64 = 100;
+/// Most widgets a dashboard may have
+pub const MAX_DASHBOARD_WIDGETS: usize = 24;
+
+/// Length of the time buckets of a series
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatisticsBucket {
+    #[default]
+    Day,
+    /// Weeks start on Monday
+    Week,
+    Month,
+}
+
+impl std::fmt::Display for StatisticsBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatisticsBucket::Day => write!(f, "day"),
+            StatisticsBucket::Week => write!(f, "week"),
+            StatisticsBucket::Month => write!(f, "month"),
+        }
+    }
+}
+
+impl StatisticsBucket {
+    /// Buckets needed to cover `from` to `to`, both included
+    pub fn count(self, from: NaiveDate, to: NaiveDate) -> i64 {
+        match self {
+            StatisticsBucket::Day => (to - from).num_days() + 1,
+            StatisticsBucket::Week => {
+                let monday = |date: NaiveDate| date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (monday(to) - monday(from)).num_days() / 7 + 1
+            }
+            StatisticsBucket::Month => {
+                let months = |date: NaiveDate| date.year() as i64 * 12 + date.month0() as i64;
+                months(to) - months(from) + 1
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+pub struct StatisticsQuery {
+    /// First day to include (default: 30 days before `to`)
+    pub from: Option<NaiveDate>,
+    /// Last day to include (default: today, UTC)
+    pub to: Option<NaiveDate>,
+    /// Bucket length of the series (default: day)
+    pub bucket: Option<StatisticsBucket>,
+    /// Only this user's documents; admins only, others always see their own
+    pub user_id: Option<Uuid>,
+    /// Correspondents and labels listed (default 10, at most 100)
+    pub top: Option<i64>,
+}
+
+impl StatisticsQuery {
+    /// The days covered and the bucket length, or why they are not acceptable
+    pub fn range(&self, today: NaiveDate) -> Result<(NaiveDate, NaiveDate, StatisticsBucket), String> {
+        let to = self.to.unwrap_or(today);
+        let from = self.from.unwrap_or(to - Duration::days(DEFAULT_STATISTICS_DAYS - 1));
+        if from > to {
+            return Err("from is after to".to_string());
+        }
+        let bucket = self.bucket.unwrap_or_default();
+        if bucket.count(from, to) > MAX_STATISTICS_BUCKETS {
+            return Err(format!(
+                "The range needs more than {} {} buckets; use a longer bucket",
+                MAX_STATISTICS_BUCKETS, bucket
+            ));
+        }
+        Ok((from, to, bucket))
+    }
+
+    pub fn top(&self) -> i64 {
+        self.top.unwrap_or(DEFAULT_STATISTICS_TOP).clamp(1, MAX_STATISTICS_TOP)
+    }
+}
+
+/// Documents uploaded in one bucket
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct IngestionBucket {
+    /// First day of the bucket
+    pub start: NaiveDate,
+    pub documents: i64,
+    pub bytes: i64,
+}
+
+/// Documents whose OCR completed in one bucket
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OcrBucket {
+    pub start: NaiveDate,
+    pub documents: i64,
+    /// Pages of their OCR text
+    pub pages: i64,
+}
+
+/// Storage taken by the documents still stored, at the end of one bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StorageBucket {
+    pub start: NaiveDate,
+    pub added_bytes: i64,
+    pub total_bytes: i64,
+}
+
+/// Storage growth from what was stored before the first bucket
+pub fn storage_growth(bytes_before: i64, ingested: &[IngestionBucket]) -> Vec<StorageBucket> {
+    let mut total_bytes = bytes_before;
+    ingested
+        .iter()
+        .map(|bucket| {
+            total_bytes += bucket.bytes;
+            StorageBucket { start: bucket.start, added_bytes: bucket.bytes, total_bytes }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CorrespondentCount {
+    pub correspondent_id: Uuid,
+    pub name: String,
+    pub documents: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LabelCount {
+    pub label_id: Uuid,
+    pub name: String,
+    pub color: String,
+    pub documents: i64,
+}
+
+/// One user's share of the range, and of everything they store
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserStatistics {
+    pub user_id: Uuid,
+    pub username: String,
+    pub documents: i64,
+    pub bytes: i64,
+    pub ocr_pages: i64,
+    pub total_documents: i64,
+    pub total_bytes: i64,
+}
+
+/// Activity over the range, in time buckets, for the whole library or one user
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Statistics {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub bucket: StatisticsBucket,
+    /// The user the figures are limited to, if any
+    pub user_id: Option<Uuid>,
+    pub ingested: Vec<IngestionBucket>,
+    pub ocr: Vec<OcrBucket>,
+    pub storage: Vec<StorageBucket>,
+    /// Correspondents of the most documents uploaded in the range
+    pub top_correspondents: Vec<CorrespondentCount>,
+    /// Labels of the most documents uploaded in the range
+    pub labels: Vec<LabelCount>,
+    /// Per user, most documents first
+    pub users: Vec<UserStatistics>,
+}
+
+/// What a dashboard widget shows
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DashboardStatistic {
+    Ingested,
+    Ocr,
+    Storage,
+    TopCorrespondents,
+    Labels,
+    Users,
+}
+
+/// A widget of a dashboard; the frontend reads its statistic for the last `days` days
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DashboardWidget {
+    /// Chosen by the client, unique within the dashboard
+    pub id: String,
+    pub statistic: DashboardStatistic,
+    pub title: Option<String>,
+    pub bucket: Option<StatisticsBucket>,
+    pub days: Option<i32>,
+}
+
+/// The widgets of a user's dashboard, in display order
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Dashboard {
+    pub widgets: Vec<DashboardWidget>,
+    /// None until the user saves a dashboard of their own
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for Dashboard {
+    fn default() -> Self {
+        let widget = |id: &str, statistic, bucket| DashboardWidget {
+            id: id.to_string(),
+            statistic,
+            title: None,
+            bucket,
+            days: Some(DEFAULT_STATISTICS_DAYS as i32),
+        };
+        Self {
+            widgets: vec![
+                widget("ingested", DashboardStatistic::Ingested, Some(StatisticsBucket::Day)),
+                widget("ocr", DashboardStatistic::Ocr, Some(StatisticsBucket::Day)),
+                widget("storage", DashboardStatistic::Storage, Some(StatisticsBucket::Day)),
+                widget("labels", DashboardStatistic::Labels, None),
+            ],
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateDashboardRequest {
+    pub widgets: Vec<DashboardWidget>,
+}
+
+impl UpdateDashboardRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.widgets.len() > MAX_DASHBOARD_WIDGETS {
+            return Err(format!("A dashboard may have at most {} widgets", MAX_DASHBOARD_WIDGETS));
+        }
+        let mut ids = std::collections::HashSet::new();
+        for widget in &self.widgets {
+            if widget.id.trim().is_empty() || widget.id.len() > 64 {
+                return Err("Widget ids must be 1 to 64 characters".to_string());
+            }
+            if !ids.insert(widget.id.as_str()) {
+                return Err(format!("Widget id '{}' is used twice", widget.id));
+            }
+            if widget.title.as_ref().is_some_and(|title| title.len() > 100) {
+                return Err(format!("Title of widget '{}' is longer than 100 characters", widget.id));
+            }
+            if widget.days.is_some_and(|days| !(1..=3660).contains(&days)) {
+                return Err(format!("days of widget '{}' must be between 1 and 3660", widget.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_statistics_range() {
+        let today = date(2025, 10, 15);
+        assert_eq!(StatisticsQuery::default().range(today), Ok((date(2025, 9, 16), today, StatisticsBucket::Day)));
+
+        let year = StatisticsQuery { from: Some(date(2023, 1, 1)), to: Some(date(2024, 12, 31)), ..Default::default() };
+        assert!(year.range(today).is_err());
+        let by_month = StatisticsQuery { bucket: Some(StatisticsBucket::Month), ..year };
+        assert!(by_month.range(today).is_ok());
+        let reversed = StatisticsQuery { from: Some(date(2025, 2, 1)), to: Some(date(2025, 1, 1)), ..Default::default() };
+        assert!(reversed.range(today).is_err());
+    }
+
+    #[test]
+    fn test_bucket_count() {
+        // Wednesday to the Monday after next
+        assert_eq!(StatisticsBucket::Week.count(date(2025, 10, 1), date(2025, 10, 13)), 3);
+        assert_eq!(StatisticsBucket::Month.count(date(2024, 11, 30), date(2025, 2, 1)), 4);
+        assert_eq!(StatisticsBucket::Day.count(date(2025, 10, 15), date(2025, 10, 15)), 1);
+    }
+
+    #[test]
+    fn test_storage_growth() {
+        let bucket = |day, bytes| IngestionBucket { start: date(2025, 10, day), documents: 1, bytes };
+        let growth = storage_growth(1000, &[bucket(1, 200), bucket(2, 0), bucket(3, 50)]);
+        let totals: Vec<i64> = growth.iter().map(|bucket| bucket.total_bytes).collect();
+        assert_eq!(totals, vec![1200, 1200, 1250]);
+    }
+}
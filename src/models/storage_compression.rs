@@ -0,0 +1,295 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+const MAX_POLICY_NAME_LENGTH: usize = 200;
+const MIN_TARGET_DPI: i32 = 72;
+const MAX_TARGET_DPI: i32 = 600;
+
+/// What oversized files are recompressed into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionFormat {
+    /// A PDF of JPEG pages with a text layer; PDFs keep their own text layer
+    Pdf,
+    /// A single JPEG image, for images only
+    Jpeg,
+}
+
+impl CompressionFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            CompressionFormat::Pdf => "application/pdf",
+            CompressionFormat::Jpeg => "image/jpeg",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            CompressionFormat::Pdf => "pdf",
+            CompressionFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+impl std::fmt::Display for CompressionFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompressionFormat::Pdf => write!(f, "pdf"),
+            CompressionFormat::Jpeg => write!(f, "jpeg"),
+        }
+    }
+}
+
+impl TryFrom<String> for CompressionFormat {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "pdf" => Ok(CompressionFormat::Pdf),
+            "jpeg" => Ok(CompressionFormat::Jpeg),
+            _ => Err(format!("Unknown compression format: {}", value)),
+        }
+    }
+}
+
+/// Recompresses stored files of some types above a size once their OCR is done
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StorageCompressionPolicy {
+    pub id: Uuid,
+    pub name: String,
+    pub enabled: bool,
+    /// Types of the files the policy applies to, e.g. `image/tiff`; `image/*` matches
+    /// every image type
+    pub mime_types: Vec<String>,
+    /// Only files at least this large are recompressed
+    pub min_file_size_bytes: i64,
+    #[sqlx(try_from = "String")]
+    pub target_format: CompressionFormat,
+    /// Resolution pages are downsampled to
+    pub target_dpi: i32,
+    pub jpeg_quality: i32,
+    /// Days the original can still be downloaded after recompression; kept until the
+    /// document is deleted when not set
+    pub keep_original_days: Option<i32>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl StorageCompressionPolicy {
+    /// Whether a stored file of this type and size is recompressed by the policy
+    pub fn applies_to(&self, mime_type: &str, file_size: i64) -> bool {
+        self.enabled
+            && file_size >= self.min_file_size_bytes
+            && self.mime_types.iter().any(|pattern| mime_type_matches(pattern, mime_type))
+            && (self.target_format == CompressionFormat::Pdf || mime_type.starts_with("image/"))
+    }
+}
+
+fn mime_type_matches(pattern: &str, mime_type: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => mime_type.split('/').next() == Some(prefix),
+        None => pattern.eq_ignore_ascii_case(mime_type),
+    }
+}
+
+fn is_compressible_type(mime_type: &str) -> bool {
+    mime_type == "application/pdf" || mime_type.starts_with("image/")
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateStorageCompressionPolicyRequest {
+    pub name: String,
+    pub enabled: Option<bool>,
+    /// PDF and image types, e.g. `image/tiff`, `image/*` or `application/pdf`
+    pub mime_types: Vec<String>,
+    pub min_file_size_bytes: Option<i64>,
+    pub target_format: CompressionFormat,
+    /// Defaults to 200
+    pub target_dpi: Option<i32>,
+    /// Defaults to 75
+    pub jpeg_quality: Option<i32>,
+    pub keep_original_days: Option<i32>,
+}
+
+impl CreateStorageCompressionPolicyRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_policy(
+            Some(&self.name),
+            Some(&self.mime_types),
+            Some(self.target_format),
+            self.min_file_size_bytes,
+            self.target_dpi,
+            self.jpeg_quality,
+            self.keep_original_days,
+        )
+    }
+}
+
+/// Fields left out are unchanged; `keep_original_days` of documents already
+/// recompressed is not changed either
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateStorageCompressionPolicyRequest {
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+    pub mime_types: Option<Vec<String>>,
+    pub min_file_size_bytes: Option<i64>,
+    pub target_format: Option<CompressionFormat>,
+    pub target_dpi: Option<i32>,
+    pub jpeg_quality: Option<i32>,
+    /// Set `clear_keep_original_days` to keep originals until their document is deleted
+    pub keep_original_days: Option<i32>,
+    #[serde(default)]
+    pub clear_keep_original_days: bool,
+}
+
+impl UpdateStorageCompressionPolicyRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_policy(
+            self.name.as_deref(),
+            self.mime_types.as_ref(),
+            self.target_format,
+            self.min_file_size_bytes,
+            self.target_dpi,
+            self.jpeg_quality,
+            self.keep_original_days,
+        )
+    }
+}
+
+fn validate_policy(
+    name: Option<&str>,
+    mime_types: Option<&Vec<String>>,
+    target_format: Option<CompressionFormat>,
+    min_file_size_bytes: Option<i64>,
+    target_dpi: Option<i32>,
+    jpeg_quality: Option<i32>,
+    keep_original_days: Option<i32>,
+) -> Result<(), String> {
+    if let Some(name) = name {
+        if name.trim().is_empty() || name.len() > MAX_POLICY_NAME_LENGTH {
+            return Err(format!("Name must be 1 to {} characters", MAX_POLICY_NAME_LENGTH));
+        }
+    }
+    if let Some(mime_types) = mime_types {
+        if mime_types.is_empty() {
+            return Err("At least one file type is required".to_string());
+        }
+        if let Some(unsupported) = mime_types.iter().find(|m| !is_compressible_type(m)) {
+            return Err(format!("Only PDFs and images can be recompressed, not {}", unsupported));
+        }
+        if target_format == Some(CompressionFormat::Jpeg) && mime_types.iter().any(|m| !m.starts_with("image/")) {
+            return Err("Only images can be recompressed as JPEG".to_string());
+        }
+    }
+    if min_file_size_bytes.is_some_and(|size| size < 0) {
+        return Err("The minimum file size cannot be negative".to_string());
+    }
+    if target_dpi.is_some_and(|dpi| !(MIN_TARGET_DPI..=MAX_TARGET_DPI).contains(&dpi)) {
+        return Err(format!("Resolution must be {} to {} dpi", MIN_TARGET_DPI, MAX_TARGET_DPI));
+    }
+    if jpeg_quality.is_some_and(|quality| !(10..=100).contains(&quality)) {
+        return Err("JPEG quality must be 10 to 100".to_string());
+    }
+    if keep_original_days.is_some_and(|days| days < 0) {
+        return Err("Days to keep originals cannot be negative".to_string());
+    }
+    Ok(())
+}
+
+/// The original of a recompressed document
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CompressedOriginal {
+    pub document_id: Uuid,
+    /// Storage id the original's file is saved under
+    #[serde(skip)]
+    pub id: Uuid,
+    pub policy_id: Option<Uuid>,
+    /// Name the original is stored under
+    #[serde(skip)]
+    pub filename: String,
+    pub original_filename: String,
+    /// None once the original was deleted
+    #[serde(skip)]
+    pub file_path: Option<String>,
+    pub file_size: i64,
+    pub file_hash: Option<String>,
+    pub mime_type: String,
+    /// Size of the recompressed file that replaced it
+    pub compressed_size: i64,
+    pub compressed_at: DateTime<Utc>,
+    /// When the original is deleted; kept until the document is deleted when not set
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When the original was deleted
+    pub purged_at: Option<DateTime<Utc>>,
+}
+
+/// Bytes saved by recompression
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StorageCompressionSummary {
+    pub documents: i64,
+    pub original_bytes: i64,
+    pub compressed_bytes: i64,
+    /// Originals still kept, which count until they are deleted
+    pub originals_kept: i64,
+    pub originals_kept_bytes: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(mime_types: &[&str], target_format: CompressionFormat) -> StorageCompressionPolicy {
+        StorageCompressionPolicy {
+            id: Uuid::new_v4(),
+            name: "Large scans".to_string(),
+            enabled: true,
+            mime_types: mime_types.iter().map(|m| m.to_string()).collect(),
+            min_file_size_bytes: 10_000_000,
+            target_format,
+            target_dpi: 200,
+            jpeg_quality: 75,
+            keep_original_days: Some(30),
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_policy_applies_to_large_files_of_its_types() {
+        let tiffs = policy(&["image/tiff"], CompressionFormat::Pdf);
+        assert!(tiffs.applies_to("image/tiff", 50_000_000));
+        assert!(!tiffs.applies_to("image/tiff", 1_000_000));
+        assert!(!tiffs.applies_to("image/png", 50_000_000));
+
+        let images = policy(&["image/*"], CompressionFormat::Jpeg);
+        assert!(images.applies_to("image/png", 50_000_000));
+        assert!(!images.applies_to("application/pdf", 50_000_000));
+    }
+
+    #[test]
+    fn test_validate_policy() {
+        let request = CreateStorageCompressionPolicyRequest {
+            name: "Large scans".to_string(),
+            enabled: None,
+            mime_types: vec!["image/tiff".to_string(), "application/pdf".to_string()],
+            min_file_size_bytes: Some(10_000_000),
+            target_format: CompressionFormat::Pdf,
+            target_dpi: Some(200),
+            jpeg_quality: None,
+            keep_original_days: Some(90),
+        };
+        assert!(request.validate().is_ok());
+
+        let jpeg_pdfs = CreateStorageCompressionPolicyRequest { target_format: CompressionFormat::Jpeg, ..request.clone() };
+        assert!(jpeg_pdfs.validate().is_err());
+        let text = CreateStorageCompressionPolicyRequest { mime_types: vec!["text/plain".to_string()], ..request.clone() };
+        assert!(text.validate().is_err());
+        let dpi = CreateStorageCompressionPolicyRequest { target_dpi: Some(1200), ..request };
+        assert!(dpi.validate().is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::{StorageMigrationFailure, StorageMigrationStatus};
+
+/// The admin's template for where document files are stored, see `StoragePathTemplate`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StorageLayout {
+    /// None when files are stored in the backend's own layout, by document id
+    pub template: Option<String>,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateStorageLayoutRequest {
+    /// e.g. `{year}/{correspondent}/{title}-{id}.pdf`; null or empty goes back to the
+    /// backend's own layout
+    pub template: Option<String>,
+    /// Move existing files into the new layout, defaults to true
+    pub relocate: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StorageLayoutUpdate {
+    pub layout: StorageLayout,
+    /// The relocation that moves existing files, when one was started
+    pub relocation: Option<StorageRelocation>,
+}
+
+/// A run that moves existing document files into the storage layout
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StorageRelocation {
+    pub id: Uuid,
+    /// The template files are moved into; None moves them back to the backend's layout
+    pub template: Option<String>,
+    #[sqlx(try_from = "String")]
+    pub status: StorageMigrationStatus,
+    pub total_documents: i64,
+    pub moved_documents: i64,
+    /// Documents already in place, or shared content blobs that are never moved
+    pub skipped_documents: i64,
+    pub failed_documents: i64,
+    pub last_document_id: Option<Uuid>,
+    #[schema(value_type = Vec<StorageMigrationFailure>)]
+    pub failures: sqlx::types::Json<Vec<StorageMigrationFailure>>,
+    pub error: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// A document as the relocation sees it: where it is and what its path is made of
+#[derive(Debug, Clone, FromRow)]
+pub struct RelocationCandidate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub filename: String,
+    pub file_path: String,
+    pub created_at: DateTime<Utc>,
+    pub original_created_at: Option<DateTime<Utc>>,
+    pub correspondent: Option<String>,
+    pub document_type: Option<String>,
+}
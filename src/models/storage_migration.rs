@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Storage backend a migration moves document files into
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum StorageBackendKind {
+    /// The upload directory, files stored as `documents/{document_id}.{ext}`
+    #[serde(rename = "local")]
+    Local,
+    /// The configured S3 bucket, keys `documents/{user_id}/{yyyy}/{mm}/{document_id}.{ext}`
+    #[serde(rename = "s3")]
+    S3,
+    /// The configured Azure Blob Storage container, blobs `documents/{user_id}/{document_id}.{ext}`
+    #[serde(rename = "azure")]
+    Azure,
+}
+
+impl std::fmt::Display for StorageBackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageBackendKind::Local => write!(f, "local"),
+            StorageBackendKind::S3 => write!(f, "s3"),
+            StorageBackendKind::Azure => write!(f, "azure"),
+        }
+    }
+}
+
+impl TryFrom<String> for StorageBackendKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "local" => Ok(StorageBackendKind::Local),
+            "s3" => Ok(StorageBackendKind::S3),
+            "azure" => Ok(StorageBackendKind::Azure),
+            _ => Err(format!("Unknown storage backend: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum StorageMigrationStatus {
+    /// In progress, or interrupted and waiting to be resumed
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "completed")]
+    Completed,
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+impl std::fmt::Display for StorageMigrationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageMigrationStatus::Running => write!(f, "running"),
+            StorageMigrationStatus::Completed => write!(f, "completed"),
+            StorageMigrationStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl TryFrom<String> for StorageMigrationStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "running" => Ok(StorageMigrationStatus::Running),
+            "completed" => Ok(StorageMigrationStatus::Completed),
+            "failed" => Ok(StorageMigrationStatus::Failed),
+            _ => Err(format!("Unknown storage migration status: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct StartStorageMigrationRequest {
+    pub target: StorageBackendKind,
+    /// Documents copied per batch, defaults to `STORAGE_MIGRATION_BATCH_SIZE`
+    pub batch_size: Option<i32>,
+    /// Delete local source files once reconciliation finds no problems
+    #[serde(default)]
+    pub delete_source: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StorageMigrationFailure {
+    pub document_id: Uuid,
+    pub source_path: String,
+    pub error: String,
+}
+
+/// Final comparison of the database against the target storage
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StorageMigrationReport {
+    pub finished_at: DateTime<Utc>,
+    /// Migrated documents whose path points at an existing file in the target
+    pub verified: i64,
+    /// Migrated documents whose path changed again or whose target file is missing
+    pub mismatched: Vec<StorageMigrationFailure>,
+    pub failed: Vec<StorageMigrationFailure>,
+    /// Documents still stored outside the target layout, e.g. uploaded during the run
+    pub remaining: i64,
+    pub source_files_deleted: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StorageMigration {
+    pub id: Uuid,
+    #[sqlx(try_from = "String")]
+    pub target_backend: StorageBackendKind,
+    #[sqlx(try_from = "String")]
+    pub status: StorageMigrationStatus,
+    pub batch_size: i32,
+    pub delete_source: bool,
+    pub total_documents: i64,
+    pub migrated_documents: i64,
+    /// Documents that were already stored in the target layout
+    pub skipped_documents: i64,
+    pub failed_documents: i64,
+    pub last_document_id: Option<Uuid>,
+    #[schema(value_type = Option<StorageMigrationReport>)]
+    pub report: Option<sqlx::types::Json<StorageMigrationReport>>,
+    pub error: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Where a user's quota comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageQuotaSource {
+    /// `DEFAULT_STORAGE_QUOTA_MB`
+    Default,
+    /// Set by an admin for this user
+    Override,
+}
+
+/// How much storage a user's documents take and how much they may use
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StorageUsage {
+    pub user_id: Uuid,
+    pub used_bytes: i64,
+    pub document_count: i64,
+    /// None when the user has no limit
+    pub quota_bytes: Option<i64>,
+    /// None when the user has no limit; 0 once the quota is used up
+    pub remaining_bytes: Option<i64>,
+    pub quota_source: StorageQuotaSource,
+}
+
+impl StorageUsage {
+    pub fn new(user_id: Uuid, used_bytes: i64, document_count: i64, quota_bytes: Option<i64>, quota_source: StorageQuotaSource) -> Self {
+        Self {
+            user_id,
+            used_bytes,
+            document_count,
+            quota_bytes,
+            remaining_bytes: quota_bytes.map(|quota| (quota - used_bytes).max(0)),
+            quota_source,
+        }
+    }
+
+    /// Whether `additional_bytes` more would go over the quota
+    pub fn would_exceed(&self, additional_bytes: i64) -> bool {
+        self.quota_bytes
+            .is_some_and(|quota| self.used_bytes.saturating_add(additional_bytes) > quota)
+    }
+}
+
+/// Override a user's quota. A null `quota_mb` exempts them from any limit.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetStorageQuotaRequest {
+    pub quota_mb: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_usage_remaining_and_exceeding() {
+        let usage = StorageUsage::new(Uuid::nil(), 900, 3, Some(1000), StorageQuotaSource::Default);
+        assert_eq!(usage.remaining_bytes, Some(100));
+        assert!(!usage.would_exceed(100));
+        assert!(usage.would_exceed(101));
+
+        let over = StorageUsage::new(Uuid::nil(), 1200, 3, Some(1000), StorageQuotaSource::Override);
+        assert_eq!(over.remaining_bytes, Some(0));
+
+        let unlimited = StorageUsage::new(Uuid::nil(), i64::MAX, 1, None, StorageQuotaSource::Override);
+        assert_eq!(unlimited.remaining_bytes, None);
+        assert!(!unlimited.would_exceed(i64::MAX));
+    }
+}
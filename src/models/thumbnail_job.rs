@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Progress of the background thumbnail generation
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ThumbnailQueueStats {
+    /// Documents waiting for their thumbnail, including failed attempts due for a retry
+    pub pending: i64,
+    pub processing: i64,
+    pub completed: i64,
+    /// Documents whose thumbnail could not be generated after all attempts
+    pub failed: i64,
+    /// Documents never queued, e.g. ingested before thumbnails were pre-generated;
+    /// the backfill queues them
+    pub not_queued: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ThumbnailBackfillResponse {
+    /// Documents queued, counting failed ones queued again
+    pub queued: i64,
+}
@@ -0,0 +1,103 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Most events returned by the cross-document timeline
+pub const MAX_TIMELINE_EVENTS: i64 = 1000;
+pub const DEFAULT_TIMELINE_EVENTS: i64 = 200;
+
+/// How much of an event's date is known
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DatePrecision {
+    Day,
+    /// Only the month: the date is the first of the month
+    Month,
+    /// Only the year: the date is January 1st
+    Year,
+}
+
+impl std::fmt::Display for DatePrecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatePrecision::Day => write!(f, "day"),
+            DatePrecision::Month => write!(f, "month"),
+            DatePrecision::Year => write!(f, "year"),
+        }
+    }
+}
+
+impl TryFrom<String> for DatePrecision {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "day" => Ok(DatePrecision::Day),
+            "month" => Ok(DatePrecision::Month),
+            "year" => Ok(DatePrecision::Year),
+            _ => Err(format!("Unknown date precision: {}", value)),
+        }
+    }
+}
+
+/// Read an extracted event date: `YYYY-MM-DD`, `YYYY-MM` or `YYYY`
+pub fn parse_event_date(value: &str) -> Option<(NaiveDate, DatePrecision)> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some((date, DatePrecision::Day));
+    }
+    let mut parts = value.splitn(2, '-');
+    let year: i32 = parts.next().filter(|year| year.len() == 4)?.parse().ok()?;
+    match parts.next() {
+        Some(month) => {
+            let month: u32 = month.parse().ok()?;
+            NaiveDate::from_ymd_opt(year, month, 1).map(|date| (date, DatePrecision::Month))
+        }
+        None => NaiveDate::from_ymd_opt(year, 1, 1).map(|date| (date, DatePrecision::Year)),
+    }
+}
+
+/// A dated event from a document's latest analysis
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TimelineEvent {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub filename: String,
+    pub event_date: NaiveDate,
+    #[sqlx(try_from = "String")]
+    pub date_precision: DatePrecision,
+    pub description: String,
+    /// Names of the entities involved
+    pub entities: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct TimelineQuery {
+    /// Only events involving this entity
+    pub entity_id: Option<Uuid>,
+    /// First event date to include
+    pub from: Option<NaiveDate>,
+    /// Last event date to include
+    pub to: Option<NaiveDate>,
+    /// Default 200, at most 1000
+    pub limit: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_event_date() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(parse_event_date("2021-05-03"), Some((date(2021, 5, 3), DatePrecision::Day)));
+        assert_eq!(parse_event_date(" 2024-01 "), Some((date(2024, 1, 1), DatePrecision::Month)));
+        assert_eq!(parse_event_date("2019"), Some((date(2019, 1, 1), DatePrecision::Year)));
+        assert_eq!(parse_event_date("2021-13"), None);
+        assert_eq!(parse_event_date("2021-02-30"), None);
+        assert_eq!(parse_event_date("next Tuesday"), None);
+        assert_eq!(parse_event_date("21"), None);
+    }
+}
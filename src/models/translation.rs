@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Names of common languages, for translation prompts
+const LANGUAGE_NAMES: [(&str, &str); 20] = [
+    ("en", "English"),
+    ("de", "German"),
+    ("fr", "French"),
+    ("es", "Spanish"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("nl", "Dutch"),
+    ("da", "Danish"),
+    ("no", "Norwegian"),
+    ("sv", "Swedish"),
+    ("fi", "Finnish"),
+    ("hu", "Hungarian"),
+    ("ro", "Romanian"),
+    ("ru", "Russian"),
+    ("tr", "Turkish"),
+    ("pl", "Polish"),
+    ("cs", "Czech"),
+    ("ja", "Japanese"),
+    ("zh", "Chinese"),
+    ("ar", "Arabic"),
+];
+
+/// The ISO 639-1 code a target language is given as, lowercased
+pub fn normalize_language_code(code: &str) -> Option<String> {
+    let code = code.trim().to_ascii_lowercase();
+    (code.len() == 2 && code.chars().all(|c| c.is_ascii_lowercase())).then_some(code)
+}
+
+/// English name of a language for translation prompts, or its code when unknown
+pub fn language_name(code: &str) -> &str {
+    LANGUAGE_NAMES.iter().find(|(known, _)| *known == code).map_or(code, |(_, name)| name)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationProvider {
+    /// The chat model set with `LLM_MODEL`
+    Llm,
+    /// The translation API set with `TRANSLATION_API_URL`
+    Api,
+}
+
+impl std::fmt::Display for TranslationProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranslationProvider::Llm => write!(f, "llm"),
+            TranslationProvider::Api => write!(f, "api"),
+        }
+    }
+}
+
+impl TryFrom<String> for TranslationProvider {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "llm" => Ok(TranslationProvider::Llm),
+            "api" => Ok(TranslationProvider::Api),
+            _ => Err(format!("Unknown translation provider: {}", value)),
+        }
+    }
+}
+
+/// A document's text translated into one language
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentTranslation {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    /// ISO 639-1 code
+    pub language: String,
+    pub text: String,
+    #[sqlx(try_from = "String")]
+    pub provider: TranslationProvider,
+    pub model: Option<String>,
+    /// Characters of the document's text that were translated
+    pub source_chars: i32,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A translation without its text, as listed
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DocumentTranslationSummary {
+    pub language: String,
+    #[sqlx(try_from = "String")]
+    pub provider: TranslationProvider,
+    pub model: Option<String>,
+    pub source_chars: i32,
+    /// Characters of the translation
+    pub chars: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct TranslateQuery {
+    /// ISO 639-1 code of the language to translate into, e.g. `en`
+    pub target: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_language_code() {
+        assert_eq!(normalize_language_code(" EN ").as_deref(), Some("en"));
+        assert_eq!(normalize_language_code("de").as_deref(), Some("de"));
+        assert_eq!(normalize_language_code("eng"), None);
+        assert_eq!(normalize_language_code("e1"), None);
+        assert_eq!(normalize_language_code(""), None);
+    }
+
+    #[test]
+    fn test_language_name() {
+        assert_eq!(language_name("fr"), "French");
+        assert_eq!(language_name("xx"), "xx");
+    }
+}
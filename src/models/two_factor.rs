@@ -0,0 +1,105 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::UserResponse;
+
+/// A user's TOTP enrollment as stored; the secret may be sealed
+#[derive(Debug, Clone, FromRow)]
+pub struct UserTwoFactor {
+    pub user_id: Uuid,
+    pub secret: String,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+    pub failed_attempts: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl UserTwoFactor {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+
+    pub fn is_locked(&self, now: DateTime<Utc>) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub recovery_codes_remaining: i64,
+    /// Whether the server requires two-factor login for local accounts
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TwoFactorSetupResponse {
+    /// Base32 secret for manual entry in an authenticator app
+    pub secret: String,
+    /// `otpauth://` URI to show as a QR code
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct TwoFactorCodeRequest {
+    /// Six-digit code from the authenticator app
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EnableTwoFactorResponse {
+    /// Single-use codes for when the authenticator is lost; shown only once
+    pub recovery_codes: Vec<String>,
+    /// Session token, when enrollment finished a login that required it
+    pub token: Option<String>,
+    pub user: Option<UserResponse>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct DisableTwoFactorRequest {
+    pub password: String,
+    /// Code from the authenticator app or a recovery code
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Returned by `POST /api/auth/login` instead of a session when a second step is needed
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TwoFactorChallenge {
+    /// Send a code with this token to `POST /api/auth/login/2fa`
+    pub two_factor_required: bool,
+    /// The server requires two-factor login and the account has not enrolled yet;
+    /// use this token to enroll under `/api/auth/2fa`
+    pub two_factor_setup_required: bool,
+    /// Valid for five minutes
+    pub challenge_token: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct TwoFactorLoginRequest {
+    pub challenge_token: String,
+    /// Code from the authenticator app or a recovery code
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AuthPolicy {
+    /// Local accounts must use two-factor login
+    pub require_two_factor: bool,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateAuthPolicyRequest {
+    pub require_two_factor: bool,
+}
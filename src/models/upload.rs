@@ -0,0 +1,214 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResumableUploadStatus {
+    /// Waiting for more bytes
+    Uploading,
+    /// Every byte was received and the file was ingested
+    Completed,
+    /// Every byte was received but the file could not be ingested
+    Failed,
+}
+
+impl std::fmt::Display for ResumableUploadStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResumableUploadStatus::Uploading => write!(f, "uploading"),
+            ResumableUploadStatus::Completed => write!(f, "completed"),
+            ResumableUploadStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl TryFrom<String> for ResumableUploadStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "uploading" => Ok(ResumableUploadStatus::Uploading),
+            "completed" => Ok(ResumableUploadStatus::Completed),
+            "failed" => Ok(ResumableUploadStatus::Failed),
+            _ => Err(format!("Unknown upload status: {}", value)),
+        }
+    }
+}
+
+/// A document uploaded in chunks with the tus protocol
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ResumableUpload {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub filename: String,
+    pub mime_type: String,
+    /// Size of the whole file in bytes
+    pub upload_length: i64,
+    /// Bytes received so far
+    pub upload_offset: i64,
+    pub ocr_language: Option<String>,
+    #[sqlx(try_from = "String")]
+    pub status: ResumableUploadStatus,
+    /// The ingested document, or the existing document the file duplicates
+    pub document_id: Option<Uuid>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// When the upload is removed unless more bytes arrive
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Files one batch upload may carry
+pub const MAX_BATCH_UPLOAD_FILES: usize = 100;
+/// Labels one file of a batch upload may get
+pub const MAX_BATCH_UPLOAD_LABELS: usize = 50;
+
+fn default_atomic() -> bool {
+    true
+}
+
+/// What to do with the files of a batch upload, sent as its `manifest` field
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct BatchUploadManifest {
+    /// Remove the batch's new documents again when any file fails, default true
+    #[serde(default = "default_atomic")]
+    pub atomic: bool,
+    /// One entry per uploaded file
+    pub files: Vec<BatchUploadManifestEntry>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct BatchUploadManifestEntry {
+    /// Name of the uploaded file this entry is for
+    pub filename: String,
+    /// Names of the user's labels, created when missing
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Existing labels by ID: the user's own or system labels
+    #[serde(default)]
+    pub label_ids: Vec<Uuid>,
+    /// Correspondent name, created when missing
+    pub correspondent: Option<String>,
+    /// Becomes the document's original creation date
+    pub document_date: Option<NaiveDate>,
+}
+
+impl BatchUploadManifest {
+    /// Checks that the manifest has exactly one entry for each of the uploaded files
+    pub fn validate(&self, filenames: &[String]) -> Result<(), String> {
+        if filenames.is_empty() {
+            return Err("No files uploaded".to_string());
+        }
+        if filenames.len() > MAX_BATCH_UPLOAD_FILES {
+            return Err(format!("At most {} files can be uploaded at once", MAX_BATCH_UPLOAD_FILES));
+        }
+        let mut seen = std::collections::HashSet::new();
+        if let Some(filename) = filenames.iter().find(|filename| !seen.insert(filename.as_str())) {
+            return Err(format!("File '{}' was uploaded twice", filename));
+        }
+
+        let mut entries = std::collections::HashSet::new();
+        for entry in &self.files {
+            if !entries.insert(entry.filename.as_str()) {
+                return Err(format!("The manifest lists '{}' twice", entry.filename));
+            }
+            if !seen.contains(entry.filename.as_str()) {
+                return Err(format!("The manifest lists '{}', which was not uploaded", entry.filename));
+            }
+            if entry.labels.len() + entry.label_ids.len() > MAX_BATCH_UPLOAD_LABELS {
+                return Err(format!("'{}' has more than {} labels", entry.filename, MAX_BATCH_UPLOAD_LABELS));
+            }
+            if entry.labels.iter().any(|label| label.trim().is_empty()) {
+                return Err(format!("'{}' has an empty label name", entry.filename));
+            }
+            if entry.correspondent.as_deref().is_some_and(|name| name.trim().is_empty()) {
+                return Err(format!("'{}' has an empty correspondent", entry.filename));
+            }
+        }
+        if let Some(filename) = filenames.iter().find(|filename| !entries.contains(filename.as_str())) {
+            return Err(format!("The manifest has no entry for '{}'", filename));
+        }
+        Ok(())
+    }
+
+    pub fn entry(&self, filename: &str) -> Option<&BatchUploadManifestEntry> {
+        self.files.iter().find(|entry| entry.filename == filename)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchUploadFileStatus {
+    Created,
+    /// The user already has the file; the existing document is left as it is
+    Duplicate,
+    Failed,
+    /// Created, then removed again because another file of an atomic batch failed
+    RolledBack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BatchUploadFileResult {
+    pub filename: String,
+    pub status: BatchUploadFileStatus,
+    /// The new document, or the existing one a duplicate matches
+    pub document_id: Option<Uuid>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BatchUploadResponse {
+    pub atomic: bool,
+    /// False when an atomic batch was rolled back
+    pub committed: bool,
+    pub created: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+    /// One result per file, in upload order
+    pub results: Vec<BatchUploadFileResult>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(filenames: &[&str]) -> BatchUploadManifest {
+        BatchUploadManifest {
+            atomic: true,
+            files: filenames
+                .iter()
+                .map(|filename| BatchUploadManifestEntry { filename: filename.to_string(), ..Default::default() })
+                .collect(),
+        }
+    }
+
+    fn uploaded(filenames: &[&str]) -> Vec<String> {
+        filenames.iter().map(|filename| filename.to_string()).collect()
+    }
+
+    #[test]
+    fn test_manifest_must_match_the_files() {
+        assert!(manifest(&["a.pdf", "b.pdf"]).validate(&uploaded(&["b.pdf", "a.pdf"])).is_ok());
+        assert!(manifest(&["a.pdf"]).validate(&uploaded(&["a.pdf", "b.pdf"])).is_err());
+        assert!(manifest(&["a.pdf", "c.pdf"]).validate(&uploaded(&["a.pdf"])).is_err());
+        assert!(manifest(&["a.pdf", "a.pdf"]).validate(&uploaded(&["a.pdf"])).is_err());
+        assert!(manifest(&["a.pdf"]).validate(&uploaded(&["a.pdf", "a.pdf"])).is_err());
+        assert!(manifest(&[]).validate(&[]).is_err());
+    }
+
+    #[test]
+    fn test_manifest_entries() {
+        let mut blank_label = manifest(&["a.pdf"]);
+        blank_label.files[0].labels = vec!["Taxes".to_string(), " ".to_string()];
+        assert!(blank_label.validate(&uploaded(&["a.pdf"])).is_err());
+
+        let json = r#"{"files": [{"filename": "a.pdf", "labels": ["Taxes"], "correspondent": "ACME", "document_date": "2024-03-01"}]}"#;
+        let parsed: BatchUploadManifest = serde_json::from_str(json).unwrap();
+        assert!(parsed.atomic);
+        assert_eq!(parsed.entry("a.pdf").unwrap().document_date, NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert!(parsed.validate(&uploaded(&["a.pdf"])).is_ok());
+    }
+}
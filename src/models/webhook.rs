@@ -0,0 +1,190 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use super::EventType;
+
+/// Which events a webhook endpoint receives. An empty list places no restriction;
+/// every non-empty list must match for an event to be delivered.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct WebhookEventFilter {
+    #[serde(default)]
+    pub event_types: Vec<EventType>,
+    /// Document tags or label names, matched case-insensitively; any one is enough
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub source_ids: Vec<Uuid>,
+    /// Document MIME types; `image/*` matches every image type
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+}
+
+/// What an event is about, as far as filters are concerned. Attributes an event
+/// does not have (e.g. tags of a sync event) never satisfy a filter on them.
+#[derive(Debug, Clone, Default)]
+pub struct WebhookEventContext {
+    pub tags: Vec<String>,
+    pub source_id: Option<Uuid>,
+    pub mime_type: Option<String>,
+}
+
+impl WebhookEventFilter {
+    pub fn matches(&self, event_type: EventType, context: &WebhookEventContext) -> bool {
+        if !self.event_types.is_empty() && !self.event_types.contains(&event_type) {
+            return false;
+        }
+
+        if !self.tags.is_empty()
+            && !self.tags.iter().any(|wanted| {
+                context.tags.iter().any(|tag| tag.eq_ignore_ascii_case(wanted))
+            })
+        {
+            return false;
+        }
+
+        if !self.source_ids.is_empty()
+            && !context.source_id.is_some_and(|source_id| self.source_ids.contains(&source_id))
+        {
+            return false;
+        }
+
+        if !self.mime_types.is_empty() {
+            let Some(mime_type) = context.mime_type.as_deref() else {
+                return false;
+            };
+            let mime_type = mime_type.to_ascii_lowercase();
+            let matched = self.mime_types.iter().any(|pattern| {
+                let pattern = pattern.to_ascii_lowercase();
+                match pattern.strip_suffix("/*") {
+                    Some(prefix) => mime_type.split('/').next() == Some(prefix),
+                    None => mime_type == pattern,
+                }
+            });
+            if !matched {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub url: String,
+    pub description: Option<String>,
+    pub enabled: bool,
+    #[schema(value_type = WebhookEventFilter)]
+    pub filter: sqlx::types::Json<WebhookEventFilter>,
+    pub last_delivery_at: Option<DateTime<Utc>>,
+    /// Error of the most recent delivery, cleared when a delivery succeeds
+    pub last_error: Option<String>,
+    /// Key deliveries are signed with; only returned when it is set
+    #[serde(skip)]
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An endpoint with its signing secret, returned when the secret is set
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WebhookEndpointWithSecret {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    /// HMAC-SHA256 key of the `X-Readur-Signature` header
+    pub secret: String,
+}
+
+impl From<WebhookEndpoint> for WebhookEndpointWithSecret {
+    fn from(endpoint: WebhookEndpoint) -> Self {
+        let secret = endpoint.secret.clone();
+        Self { endpoint, secret }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateWebhookEndpointRequest {
+    pub url: String,
+    pub description: Option<String>,
+    /// Defaults to true
+    pub enabled: Option<bool>,
+    /// Receive every event when omitted
+    #[serde(default)]
+    pub filter: WebhookEventFilter,
+    /// Signing secret of at least 16 characters, generated when omitted
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateWebhookEndpointRequest {
+    pub url: Option<String>,
+    pub description: Option<String>,
+    pub enabled: Option<bool>,
+    /// Replaces the whole filter
+    pub filter: Option<WebhookEventFilter>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookDeliveryStatus {
+    /// Not delivered yet; retried at `next_attempt_at`
+    Pending,
+    Succeeded,
+    /// Every attempt failed
+    Failed,
+}
+
+impl std::fmt::Display for WebhookDeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookDeliveryStatus::Pending => write!(f, "pending"),
+            WebhookDeliveryStatus::Succeeded => write!(f, "succeeded"),
+            WebhookDeliveryStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl TryFrom<String> for WebhookDeliveryStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "pending" => Ok(WebhookDeliveryStatus::Pending),
+            "succeeded" => Ok(WebhookDeliveryStatus::Succeeded),
+            "failed" => Ok(WebhookDeliveryStatus::Failed),
+            _ => Err(format!("Unknown webhook delivery status: {}", value)),
+        }
+    }
+}
+
+/// One event sent, or being sent, to one endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: Uuid,
+    #[sqlx(try_from = "String")]
+    pub event_type: EventType,
+    #[sqlx(try_from = "String")]
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// HTTP status of the last attempt, if the endpoint responded
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct WebhookDeliveryQuery {
+    pub status: Option<WebhookDeliveryStatus>,
+    /// Default 50, at most 200
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Work a process takes on; a process runs all of it unless `NODE_ROLES` narrows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+    /// The HTTP API and web interface
+    Api,
+    /// The OCR queue, and thumbnails behind it
+    Ocr,
+    /// LLM analysis batches
+    Analysis,
+    /// Source syncs, watched folders and bucket notifications
+    Sync,
+    /// Scheduled tasks such as retention, digests and backups, and jobs resumed after
+    /// a restart
+    Maintenance,
+}
+
+impl NodeRole {
+    pub const ALL: [NodeRole; 5] =
+        [NodeRole::Api, NodeRole::Ocr, NodeRole::Analysis, NodeRole::Sync, NodeRole::Maintenance];
+}
+
+impl std::fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeRole::Api => write!(f, "api"),
+            NodeRole::Ocr => write!(f, "ocr"),
+            NodeRole::Analysis => write!(f, "analysis"),
+            NodeRole::Sync => write!(f, "sync"),
+            NodeRole::Maintenance => write!(f, "maintenance"),
+        }
+    }
+}
+
+impl TryFrom<String> for NodeRole {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "api" => Ok(NodeRole::Api),
+            "ocr" => Ok(NodeRole::Ocr),
+            "analysis" => Ok(NodeRole::Analysis),
+            "sync" => Ok(NodeRole::Sync),
+            "maintenance" => Ok(NodeRole::Maintenance),
+            _ => Err(format!("Unknown node role: {}", value)),
+        }
+    }
+}
+
+/// Roles from a comma-separated list such as `ocr,analysis`; `all` stands for every role
+pub fn parse_node_roles(value: &str) -> Result<Vec<NodeRole>, String> {
+    let mut roles = Vec::new();
+    for part in value.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+        if part.eq_ignore_ascii_case("all") {
+            return Ok(NodeRole::ALL.to_vec());
+        }
+        let role = NodeRole::try_from(part.to_string())?;
+        if !roles.contains(&role) {
+            roles.push(role);
+        }
+    }
+    if roles.is_empty() {
+        return Err("At least one node role is required".to_string());
+    }
+    Ok(roles)
+}
+
+/// A process sharing the database, as it last reported
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WorkerNode {
+    pub id: Uuid,
+    pub name: String,
+    pub hostname: String,
+    pub roles: Vec<String>,
+    pub version: String,
+    /// Id OCR queue items claimed by this node carry
+    pub ocr_worker_id: Option<String>,
+    pub active_ocr_jobs: i32,
+    pub max_ocr_jobs: i32,
+    /// Background tasks that run on one node at a time and run on this one
+    pub exclusive_tasks: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub last_heartbeat_at: DateTime<Utc>,
+    pub stopped_at: Option<DateTime<Utc>>,
+    /// Whether the node reported within the last minute
+    pub online: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_node_roles() {
+        assert_eq!(parse_node_roles("ocr, analysis,ocr").unwrap(), vec![NodeRole::Ocr, NodeRole::Analysis]);
+        assert_eq!(parse_node_roles("all").unwrap(), NodeRole::ALL.to_vec());
+        assert_eq!(parse_node_roles("API").unwrap(), vec![NodeRole::Api]);
+        assert!(parse_node_roles("").is_err());
+        assert!(parse_node_roles("ocr,indexer").is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use super::{DocumentResponse, MAX_BULK_DOCUMENTS};
+
+/// Where a document is in the review routine: new documents land in the inbox, are
+/// reviewed, and are then filed or rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "document_workflow_state", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowState {
+    Inbox,
+    Reviewed,
+    Filed,
+    Rejected,
+}
+
+impl WorkflowState {
+    pub const ALL: [WorkflowState; 4] =
+        [WorkflowState::Inbox, WorkflowState::Reviewed, WorkflowState::Filed, WorkflowState::Rejected];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WorkflowState::Inbox => "inbox",
+            WorkflowState::Reviewed => "reviewed",
+            WorkflowState::Filed => "filed",
+            WorkflowState::Rejected => "rejected",
+        }
+    }
+
+    /// Whether a document in this state may be moved to `to`. Documents are reviewed
+    /// before they are filed; filed and rejected documents can only go back to the inbox.
+    pub fn can_transition_to(self, to: WorkflowState) -> bool {
+        use WorkflowState::*;
+        matches!(
+            (self, to),
+            (Inbox, Reviewed)
+                | (Inbox, Rejected)
+                | (Reviewed, Filed)
+                | (Reviewed, Rejected)
+                | (Reviewed, Inbox)
+                | (Filed, Inbox)
+                | (Rejected, Inbox)
+        )
+    }
+
+    /// The states a document can be moved to `to` from
+    pub fn sources_of(to: WorkflowState) -> Vec<WorkflowState> {
+        WorkflowState::ALL.into_iter().filter(|from| from.can_transition_to(to)).collect()
+    }
+
+    /// Whether documents in this state show up in search for users who require review
+    pub fn is_reviewed(self) -> bool {
+        matches!(self, WorkflowState::Reviewed | WorkflowState::Filed)
+    }
+}
+
+/// Number of documents in each state
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct WorkflowCounts {
+    pub inbox: i64,
+    pub reviewed: i64,
+    pub filed: i64,
+    pub rejected: i64,
+}
+
+impl WorkflowCounts {
+    pub fn add(&mut self, state: WorkflowState, count: i64) {
+        match state {
+            WorkflowState::Inbox => self.inbox += count,
+            WorkflowState::Reviewed => self.reviewed += count,
+            WorkflowState::Filed => self.filed += count,
+            WorkflowState::Rejected => self.rejected += count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct WorkflowDocumentsQuery {
+    /// State to list (default: `inbox`)
+    pub state: Option<WorkflowState>,
+    /// Maximum number of documents to return (default: 50)
+    pub limit: Option<i64>,
+    /// Number of documents to skip (default: 0)
+    pub offset: Option<i64>,
+}
+
+/// A document in the review routine, oldest first within its state
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkflowDocument {
+    #[serde(flatten)]
+    pub document: DocumentResponse,
+    pub workflow_state: WorkflowState,
+    pub workflow_state_changed_at: Option<DateTime<Utc>>,
+}
+
+/// Move documents to another state
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkflowTransitionRequest {
+    pub document_ids: Vec<Uuid>,
+    pub to_state: WorkflowState,
+}
+
+impl WorkflowTransitionRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.document_ids.is_empty() {
+            return Err("No documents given".to_string());
+        }
+        if self.document_ids.len() > MAX_BULK_DOCUMENTS {
+            return Err(format!("At most {} documents can be moved at once", MAX_BULK_DOCUMENTS));
+        }
+        Ok(())
+    }
+}
+
+/// A document that was left where it was
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SkippedTransition {
+    pub document_id: Uuid,
+    /// The document's state, or None when it was not found or may not be edited
+    pub state: Option<WorkflowState>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkflowTransitionResponse {
+    pub to_state: WorkflowState,
+    /// Documents moved to `to_state`
+    pub updated: Vec<Uuid>,
+    pub skipped: Vec<SkippedTransition>,
+    /// Counts after the move
+    pub counts: WorkflowCounts,
+}
+
+/// Whether documents the user has not reviewed yet are left out of search
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkflowSettings {
+    pub require_review_before_search: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workflow_transitions() {
+        assert!(WorkflowState::Inbox.can_transition_to(WorkflowState::Reviewed));
+        assert!(WorkflowState::Reviewed.can_transition_to(WorkflowState::Filed));
+        assert!(WorkflowState::Filed.can_transition_to(WorkflowState::Inbox));
+        assert!(!WorkflowState::Inbox.can_transition_to(WorkflowState::Filed));
+        assert!(!WorkflowState::Rejected.can_transition_to(WorkflowState::Filed));
+        assert!(!WorkflowState::Inbox.can_transition_to(WorkflowState::Inbox));
+
+        assert_eq!(WorkflowState::sources_of(WorkflowState::Filed), vec![WorkflowState::Reviewed]);
+        assert_eq!(
+            WorkflowState::sources_of(WorkflowState::Inbox),
+            vec![WorkflowState::Reviewed, WorkflowState::Filed, WorkflowState::Rejected]
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::fmt;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::Settings;
+
+/// What a member may do in a workspace. Each level includes the ones before it, so
+/// they compare in that order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "workspace_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceRole {
+    /// Find, open and download the workspace's documents
+    Viewer,
+    /// Also upload, rename, label and delete them, and manage its labels and sources
+    Editor,
+    /// Also manage the workspace's members and settings
+    Admin,
+}
+
+impl fmt::Display for WorkspaceRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkspaceRole::Viewer => write!(f, "viewer"),
+            WorkspaceRole::Editor => write!(f, "editor"),
+            WorkspaceRole::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Settings a workspace imposes on documents processed in it, over those of the
+/// uploading user
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct WorkspaceSettings {
+    pub ocr_language: Option<String>,
+    pub preferred_languages: Option<Vec<String>>,
+    pub enable_image_preprocessing: Option<bool>,
+    pub auto_rotate_images: Option<bool>,
+    pub ocr_min_confidence: Option<f32>,
+}
+
+impl WorkspaceSettings {
+    pub fn apply_to(&self, settings: &mut Settings) {
+        if let Some(ocr_language) = &self.ocr_language {
+            settings.ocr_language = ocr_language.clone();
+            settings.primary_language = ocr_language.clone();
+        }
+        if let Some(preferred_languages) = self.preferred_languages.as_ref().filter(|l| !l.is_empty()) {
+            settings.preferred_languages = preferred_languages.clone();
+            if self.ocr_language.is_none() {
+                settings.primary_language = preferred_languages[0].clone();
+                settings.ocr_language = preferred_languages[0].clone();
+            }
+        }
+        if let Some(enable_image_preprocessing) = self.enable_image_preprocessing {
+            settings.enable_image_preprocessing = enable_image_preprocessing;
+        }
+        if let Some(auto_rotate_images) = self.auto_rotate_images {
+            settings.auto_rotate_images = auto_rotate_images;
+        }
+        if let Some(ocr_min_confidence) = self.ocr_min_confidence {
+            settings.ocr_min_confidence = ocr_min_confidence;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Workspace {
+    pub id: Uuid,
+    pub name: String,
+    /// Unique, URL-safe identifier
+    pub slug: String,
+    #[schema(value_type = WorkspaceSettings)]
+    pub settings: sqlx::types::Json<WorkspaceSettings>,
+    pub created_by: Option<Uuid>,
+    pub member_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A workspace the current user belongs to, with their role in it
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WorkspaceMembership {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub role: WorkspaceRole,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WorkspaceMember {
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: WorkspaceRole,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateWorkspaceRequest {
+    pub name: String,
+    /// Derived from the name when missing
+    pub slug: Option<String>,
+    pub settings: Option<WorkspaceSettings>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateWorkspaceRequest {
+    pub name: Option<String>,
+    /// Replaces the whole settings object
+    pub settings: Option<WorkspaceSettings>,
+}
+
+/// Add a user to a workspace, or change the role of a member
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetWorkspaceMemberRequest {
+    pub user_id: Uuid,
+    /// Default `editor`
+    pub role: Option<WorkspaceRole>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_role_levels() {
+        assert!(WorkspaceRole::Viewer < WorkspaceRole::Editor);
+        assert!(WorkspaceRole::Editor < WorkspaceRole::Admin);
+        assert_eq!(serde_json::to_string(&WorkspaceRole::Viewer).unwrap(), "\"viewer\"");
+    }
+
+    #[test]
+    fn test_workspace_settings_override_languages() {
+        let mut settings = Settings::default();
+        WorkspaceSettings {
+            preferred_languages: Some(vec!["deu".to_string(), "eng".to_string()]),
+            enable_image_preprocessing: Some(true),
+            ..Default::default()
+        }
+        .apply_to(&mut settings);
+
+        assert_eq!(settings.preferred_languages, vec!["deu", "eng"]);
+        assert_eq!(settings.ocr_language, "deu");
+        assert_eq!(settings.primary_language, "deu");
+        assert!(settings.enable_image_preprocessing);
+    }
+}
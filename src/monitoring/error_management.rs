@@ -33,6 +33,8 @@ pub enum ErrorCategory {
     Auth,
     /// Configuration and setup issues
     Config,
+    /// Bugs such as panics in handlers or workers
+    Internal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,3 +1,7 @@
 pub mod db_monitoring;
 pub mod error_management;
-pub mod request_throttler;
\ No newline at end of file
+pub mod prometheus;
+pub mod request_throttler;
+pub mod slow_operations;
+pub mod telemetry;
+
@@ -0,0 +1,254 @@
+//! Counters and histograms of what this server did since startup, for `/metrics`:
+//! API request latency per route, OCR job durations, source sync outcomes and LLM
+//! token usage. Figures read from the database, like queue depth and storage bytes,
+//! are collected by the endpoint itself.
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::models::SourceSyncRunOutcome;
+
+/// Upper bounds in seconds of the API request latency buckets
+const REQUEST_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Upper bounds in seconds of the OCR job duration buckets
+const OCR_JOB_BUCKETS: &[f64] = &[1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0];
+
+static METRICS: Lazy<MetricsRegistry> = Lazy::new(MetricsRegistry::default);
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: &'static [f64],
+    /// Observations per bucket, not yet cumulative
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self { bounds, counts: vec![0; bounds.len()], sum: 0.0, count: 0 }
+    }
+
+    fn observe(&mut self, value: f64) {
+        if let Some(bucket) = self.bounds.iter().position(|bound| value <= *bound) {
+            self.counts[bucket] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, output: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            cumulative += count;
+            let _ = writeln!(output, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, separator, bound, cumulative);
+        }
+        let _ = writeln!(output, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, labels, separator, self.count);
+        let _ = writeln!(output, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(output, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsRegistry {
+    /// By method, route and status code
+    http_requests: Mutex<BTreeMap<(String, String, u16), Histogram>>,
+    /// By outcome
+    ocr_jobs: Mutex<BTreeMap<&'static str, Histogram>>,
+    /// By source type and status
+    sync_runs: Mutex<BTreeMap<(String, &'static str), u64>>,
+    /// By source type and what happened to the file
+    sync_files: Mutex<BTreeMap<(String, &'static str), u64>>,
+    /// By model and whether the tokens were sent or generated
+    llm_tokens: Mutex<BTreeMap<(String, &'static str), u64>>,
+}
+
+impl MetricsRegistry {
+    fn observe_http_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        if let Ok(mut requests) = self.http_requests.lock() {
+            requests
+                .entry((method.to_string(), route.to_string(), status))
+                .or_insert_with(|| Histogram::new(REQUEST_BUCKETS))
+                .observe(duration.as_secs_f64());
+        }
+    }
+
+    fn observe_ocr_job(&self, succeeded: bool, duration: Duration) {
+        if let Ok(mut jobs) = self.ocr_jobs.lock() {
+            jobs.entry(if succeeded { "succeeded" } else { "failed" })
+                .or_insert_with(|| Histogram::new(OCR_JOB_BUCKETS))
+                .observe(duration.as_secs_f64());
+        }
+    }
+
+    fn record_sync_run(&self, source_type: &str, outcome: &SourceSyncRunOutcome) {
+        if let Ok(mut runs) = self.sync_runs.lock() {
+            *runs.entry((source_type.to_string(), outcome.status)).or_default() += 1;
+        }
+        if let Ok(mut files) = self.sync_files.lock() {
+            for (result, count) in [
+                ("added", outcome.files_added),
+                ("updated", outcome.files_updated),
+                ("skipped", outcome.files_skipped),
+                ("failed", outcome.files_failed),
+            ] {
+                *files.entry((source_type.to_string(), result)).or_default() += count.max(0) as u64;
+            }
+        }
+    }
+
+    fn record_llm_tokens(&self, model: &str, prompt_tokens: u64, completion_tokens: u64) {
+        if let Ok(mut tokens) = self.llm_tokens.lock() {
+            *tokens.entry((model.to_string(), "prompt")).or_default() += prompt_tokens;
+            *tokens.entry((model.to_string(), "completion")).or_default() += completion_tokens;
+        }
+    }
+
+    fn render(&self, output: &mut String) {
+        if let Ok(requests) = self.http_requests.lock() {
+            let name = "readur_http_request_duration_seconds";
+            let _ = writeln!(output, "# HELP {} API request latency by route", name);
+            let _ = writeln!(output, "# TYPE {} histogram", name);
+            for ((method, route, status), histogram) in requests.iter() {
+                let labels = format!(
+                    "method=\"{}\",route=\"{}\",status=\"{}\"",
+                    escape_label(method),
+                    escape_label(route),
+                    status
+                );
+                histogram.render(output, name, &labels);
+            }
+        }
+
+        if let Ok(jobs) = self.ocr_jobs.lock() {
+            let name = "readur_ocr_job_duration_seconds";
+            let _ = writeln!(output, "# HELP {} Duration of OCR jobs processed by this server", name);
+            let _ = writeln!(output, "# TYPE {} histogram", name);
+            for (outcome, histogram) in jobs.iter() {
+                histogram.render(output, name, &format!("outcome=\"{}\"", outcome));
+            }
+        }
+
+        if let Ok(runs) = self.sync_runs.lock() {
+            let name = "readur_sync_runs_total";
+            let _ = writeln!(output, "# HELP {} Source syncs run by this server by outcome", name);
+            let _ = writeln!(output, "# TYPE {} counter", name);
+            for ((source_type, status), count) in runs.iter() {
+                let _ = writeln!(output, "{}{{source_type=\"{}\",status=\"{}\"}} {}", name, escape_label(source_type), status, count);
+            }
+        }
+
+        if let Ok(files) = self.sync_files.lock() {
+            let name = "readur_sync_files_total";
+            let _ = writeln!(output, "# HELP {} Files seen by source syncs by what happened to them", name);
+            let _ = writeln!(output, "# TYPE {} counter", name);
+            for ((source_type, result), count) in files.iter() {
+                let _ = writeln!(output, "{}{{source_type=\"{}\",result=\"{}\"}} {}", name, escape_label(source_type), result, count);
+            }
+        }
+
+        if let Ok(tokens) = self.llm_tokens.lock() {
+            let name = "readur_llm_tokens_total";
+            let _ = writeln!(output, "# HELP {} LLM tokens used, as reported by the LLM API", name);
+            let _ = writeln!(output, "# TYPE {} counter", name);
+            for ((model, kind), count) in tokens.iter() {
+                let _ = writeln!(output, "{}{{model=\"{}\",kind=\"{}\"}} {}", name, escape_label(model), kind, count);
+            }
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Time every request that matched a route, labelled with the route's pattern such
+/// as `/api/documents/{id}` so the number of series stays bounded
+pub async fn track_http_metrics(request: Request, next: Next) -> Response {
+    let Some(route) = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string()) else {
+        return next.run(request).await;
+    };
+    let method = request.method().to_string();
+    let started = Instant::now();
+    let response = next.run(request).await;
+    METRICS.observe_http_request(&method, &route, response.status().as_u16(), started.elapsed());
+    response
+}
+
+pub fn observe_ocr_job(succeeded: bool, duration: Duration) {
+    METRICS.observe_ocr_job(succeeded, duration);
+}
+
+pub fn record_sync_run(source_type: &str, outcome: &SourceSyncRunOutcome) {
+    METRICS.record_sync_run(source_type, outcome);
+}
+
+/// Add the `usage` an OpenAI-compatible API reported for a completion
+pub fn record_llm_usage(model: &str, response: &serde_json::Value) {
+    let usage = &response["usage"];
+    let prompt_tokens = usage["prompt_tokens"].as_u64().unwrap_or(0);
+    let completion_tokens = usage["completion_tokens"].as_u64().unwrap_or(0);
+    if prompt_tokens > 0 || completion_tokens > 0 {
+        METRICS.record_llm_tokens(model, prompt_tokens, completion_tokens);
+    }
+}
+
+/// Append the metrics in Prometheus text format
+pub fn render(output: &mut String) {
+    METRICS.render(output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let registry = MetricsRegistry::default();
+        registry.observe_http_request("GET", "/api/documents/{id}", 200, Duration::from_millis(30));
+        registry.observe_http_request("GET", "/api/documents/{id}", 200, Duration::from_millis(300));
+        registry.observe_http_request("GET", "/api/documents/{id}", 200, Duration::from_secs(60));
+
+        let mut output = String::new();
+        registry.render(&mut output);
+        let labels = "method=\"GET\",route=\"/api/documents/{id}\",status=\"200\"";
+        assert!(output.contains(&format!("readur_http_request_duration_seconds_bucket{{{},le=\"0.025\"}} 0", labels)));
+        assert!(output.contains(&format!("readur_http_request_duration_seconds_bucket{{{},le=\"0.05\"}} 1", labels)));
+        assert!(output.contains(&format!("readur_http_request_duration_seconds_bucket{{{},le=\"30\"}} 2", labels)));
+        assert!(output.contains(&format!("readur_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 3", labels)));
+        assert!(output.contains(&format!("readur_http_request_duration_seconds_count{{{}}} 3", labels)));
+    }
+
+    #[test]
+    fn test_sync_runs_and_tokens_are_counted() {
+        let registry = MetricsRegistry::default();
+        let outcome = SourceSyncRunOutcome {
+            status: "failed",
+            error: Some("2 files could not be ingested".to_string()),
+            files_added: 3,
+            files_updated: 0,
+            files_skipped: 10,
+            files_failed: 2,
+            file_errors: Vec::new(),
+        };
+        registry.record_sync_run("webdav", &outcome);
+        registry.record_sync_run("webdav", &outcome);
+        registry.record_llm_tokens("gpt-4o \"mini\"", 120, 30);
+
+        let mut output = String::new();
+        registry.render(&mut output);
+        assert!(output.contains("readur_sync_runs_total{source_type=\"webdav\",status=\"failed\"} 2"));
+        assert!(output.contains("readur_sync_files_total{source_type=\"webdav\",result=\"skipped\"} 20"));
+        assert!(output.contains("readur_llm_tokens_total{model=\"gpt-4o \\\"mini\\\"\",kind=\"prompt\"} 120"));
+    }
+}
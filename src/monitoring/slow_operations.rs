@@ -0,0 +1,245 @@
+//! Always-on record of recent search queries, OCR jobs, LLM calls and source syncs
+//! with their parameters and timings, so the slowest ones can be looked at when
+//! someone reports that Readur is slow.
+//!
+//! Each category keeps its most recent operations in a fixed-size ring buffer
+//! (`SLOW_OPERATIONS_BUFFER_SIZE`, default 500), so memory use is bounded and old
+//! outliers age out.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_BUFFER_SIZE: usize = 500;
+/// Longest string parameter kept, so a huge search query cannot bloat the buffer
+const MAX_PARAMETER_CHARS: usize = 200;
+
+static PROFILER: Lazy<SlowOperationProfiler> = Lazy::new(|| {
+    let capacity = std::env::var("SLOW_OPERATIONS_BUFFER_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_BUFFER_SIZE)
+        .max(1);
+    SlowOperationProfiler::new(capacity)
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationCategory {
+    Search,
+    Ocr,
+    Llm,
+    Sync,
+}
+
+impl OperationCategory {
+    pub const ALL: [OperationCategory; 4] = [
+        OperationCategory::Search,
+        OperationCategory::Ocr,
+        OperationCategory::Llm,
+        OperationCategory::Sync,
+    ];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RecordedOperation {
+    /// What ran, e.g. `search.enhanced` or `llm.complete`
+    pub name: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub succeeded: bool,
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OperationCategoryReport {
+    pub category: OperationCategory,
+    /// Operations recorded since startup or the last reset
+    pub total_recorded: u64,
+    /// Operations still in the buffer, which the figures below are based on
+    pub buffered: usize,
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+    /// Slowest buffered operations, slowest first
+    pub slowest: Vec<RecordedOperation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SlowOperationsReport {
+    pub buffer_size: usize,
+    pub categories: Vec<OperationCategoryReport>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+pub struct SlowOperationsQuery {
+    /// Only report this category
+    pub category: Option<OperationCategory>,
+    /// Slowest operations listed per category (default 20, at most 100)
+    pub limit: Option<usize>,
+}
+
+#[derive(Default)]
+struct CategoryBuffer {
+    total_recorded: u64,
+    operations: VecDeque<RecordedOperation>,
+}
+
+pub struct SlowOperationProfiler {
+    capacity: usize,
+    buffers: Mutex<HashMap<OperationCategory, CategoryBuffer>>,
+}
+
+impl SlowOperationProfiler {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, buffers: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn push(&self, category: OperationCategory, operation: RecordedOperation) {
+        let mut buffers = self.buffers.lock().unwrap_or_else(|e| e.into_inner());
+        let buffer = buffers.entry(category).or_default();
+        if buffer.operations.len() == self.capacity {
+            buffer.operations.pop_front();
+        }
+        buffer.operations.push_back(operation);
+        buffer.total_recorded += 1;
+    }
+
+    pub fn report(&self, category: Option<OperationCategory>, limit: usize) -> SlowOperationsReport {
+        let buffers = self.buffers.lock().unwrap_or_else(|e| e.into_inner());
+        let categories = OperationCategory::ALL
+            .into_iter()
+            .filter(|c| category.is_none_or(|wanted| wanted == *c))
+            .map(|c| match buffers.get(&c) {
+                Some(buffer) => summarize(c, buffer.total_recorded, &buffer.operations, limit),
+                None => summarize(c, 0, &VecDeque::new(), limit),
+            })
+            .collect();
+
+        SlowOperationsReport { buffer_size: self.capacity, categories }
+    }
+
+    pub fn clear(&self) {
+        self.buffers.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+fn summarize(
+    category: OperationCategory,
+    total_recorded: u64,
+    operations: &VecDeque<RecordedOperation>,
+    limit: usize,
+) -> OperationCategoryReport {
+    let mut by_duration: Vec<&RecordedOperation> = operations.iter().collect();
+    by_duration.sort_by(|a, b| b.duration_ms.cmp(&a.duration_ms).then(b.started_at.cmp(&a.started_at)));
+
+    // Nearest-rank percentile over the buffer, which is sorted slowest first
+    let percentile = |p: usize| -> Option<u64> {
+        let rank = (by_duration.len() * p).div_ceil(100).max(1);
+        by_duration.get(by_duration.len().checked_sub(rank)?).map(|op| op.duration_ms)
+    };
+
+    OperationCategoryReport {
+        category,
+        total_recorded,
+        buffered: operations.len(),
+        p50_ms: percentile(50),
+        p95_ms: percentile(95),
+        max_ms: by_duration.first().map(|op| op.duration_ms),
+        slowest: by_duration.into_iter().take(limit).cloned().collect(),
+    }
+}
+
+/// Shorten long string parameters so they fit the buffer
+fn truncate_parameters(parameters: serde_json::Value) -> serde_json::Value {
+    match parameters {
+        serde_json::Value::String(s) if s.chars().count() > MAX_PARAMETER_CHARS => {
+            let mut short: String = s.chars().take(MAX_PARAMETER_CHARS).collect();
+            short.push('…');
+            serde_json::Value::String(short)
+        }
+        serde_json::Value::Object(map) => {
+            serde_json::Value::Object(map.into_iter().map(|(k, v)| (k, truncate_parameters(v))).collect())
+        }
+        other => other,
+    }
+}
+
+/// Record an operation that started at `started` and has just finished
+pub fn record(
+    category: OperationCategory,
+    name: &str,
+    started: Instant,
+    succeeded: bool,
+    parameters: serde_json::Value,
+) {
+    let elapsed = started.elapsed();
+    let started_at = Utc::now() - chrono::Duration::from_std(elapsed).unwrap_or_default();
+    PROFILER.push(
+        category,
+        RecordedOperation {
+            name: name.to_string(),
+            started_at,
+            duration_ms: elapsed.as_millis() as u64,
+            succeeded,
+            parameters: truncate_parameters(parameters),
+        },
+    );
+}
+
+pub fn report(category: Option<OperationCategory>, limit: usize) -> SlowOperationsReport {
+    PROFILER.report(category, limit)
+}
+
+pub fn clear() {
+    PROFILER.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn operation(duration_ms: u64) -> RecordedOperation {
+        RecordedOperation {
+            name: "search.basic".to_string(),
+            started_at: Utc::now(),
+            duration_ms,
+            succeeded: true,
+            parameters: json!({ "query": "x".repeat(300) }),
+        }
+    }
+
+    #[test]
+    fn test_keeps_recent_operations_and_reports_slowest() {
+        let profiler = SlowOperationProfiler::new(10);
+        for duration_ms in 1..=20 {
+            profiler.push(OperationCategory::Search, operation(duration_ms));
+        }
+        profiler.push(OperationCategory::Ocr, operation(5));
+
+        let report = profiler.report(Some(OperationCategory::Search), 3);
+        assert_eq!(report.categories.len(), 1);
+        let search = &report.categories[0];
+        assert_eq!(search.total_recorded, 20);
+        assert_eq!(search.buffered, 10);
+        let slowest: Vec<u64> = search.slowest.iter().map(|op| op.duration_ms).collect();
+        assert_eq!(slowest, vec![20, 19, 18]);
+        assert_eq!((search.p50_ms, search.p95_ms, search.max_ms), (Some(15), Some(20), Some(20)));
+
+        let everything = profiler.report(None, 3);
+        assert_eq!(everything.categories.len(), OperationCategory::ALL.len());
+        assert_eq!(everything.categories[2].p50_ms, None);
+    }
+
+    #[test]
+    fn test_truncates_long_parameters() {
+        let truncated = truncate_parameters(json!({ "query": "x".repeat(300), "limit": 25 }));
+        assert_eq!(truncated["query"].as_str().unwrap().chars().count(), MAX_PARAMETER_CHARS + 1);
+        assert_eq!(truncated["limit"], 25);
+    }
+}
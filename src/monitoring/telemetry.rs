@@ -0,0 +1,172 @@
+//! OpenTelemetry tracing: spans of `tracing` are exported over OTLP when an endpoint
+//! is configured, and W3C trace context is read from incoming requests and written
+//! to outgoing ones so a trace follows a document through the API, the OCR queue,
+//! source syncs and the LLM and WebDAV servers.
+//!
+//! Configured with the standard variables: `OTEL_EXPORTER_OTLP_ENDPOINT` (or
+//! `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) turns the exporter on, `OTEL_SERVICE_NAME`
+//! names the service and `OTEL_TRACES_SAMPLER`/`OTEL_TRACES_SAMPLER_ARG` choose
+//! which traces are kept.
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    trace::TracerProvider as _,
+    KeyValue,
+};
+use opentelemetry_sdk::{propagation::TraceContextPropagator, runtime, trace::TracerProvider, Resource};
+use std::collections::HashMap;
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+const DEFAULT_SERVICE_NAME: &str = "readur";
+
+/// Flushes the spans still waiting for export when dropped at shutdown
+pub struct TelemetryGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Whether an OTLP endpoint is configured
+pub fn enabled() -> bool {
+    ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"]
+        .iter()
+        .any(|name| std::env::var(name).is_ok_and(|value| !value.trim().is_empty()))
+}
+
+/// Set up the OTLP exporter and return the layer that feeds it spans, or no layer
+/// when no endpoint is configured. Must be called within the Tokio runtime.
+pub fn init<S>() -> (Option<OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>, TelemetryGuard)
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    if !enabled() {
+        return (None, TelemetryGuard { provider: None });
+    }
+
+    // The endpoint, headers and timeout are read from the OTEL_EXPORTER_OTLP_* variables
+    let exporter = match opentelemetry_otlp::SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("OpenTelemetry export disabled, the OTLP exporter could not be created: {}", e);
+            return (None, TelemetryGuard { provider: None });
+        }
+    };
+
+    let service_name = std::env::var("OTEL_SERVICE_NAME")
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string());
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new(vec![
+            KeyValue::new("service.name", service_name),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]))
+        .build();
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer(DEFAULT_SERVICE_NAME));
+    (Some(layer), TelemetryGuard { provider: Some(provider) })
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+#[derive(Default)]
+struct HeaderInjector(HashMap<String, String>);
+
+impl Injector for HeaderInjector {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+}
+
+/// Run every request that matched a route in a server span, continuing the trace
+/// of the caller when it sent a `traceparent` header
+pub async fn trace_http_requests(request: Request, next: Next) -> Response {
+    let Some(route) = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string()) else {
+        return next.run(request).await;
+    };
+    let method = request.method().to_string();
+    let span = tracing::info_span!(
+        "http.request",
+        otel.name = %format!("{} {}", method, route),
+        otel.kind = "server",
+        otel.status_code = tracing::field::Empty,
+        http.request.method = %method,
+        http.route = %route,
+        http.response.status_code = tracing::field::Empty,
+    );
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(request.headers())));
+    span.set_parent(parent);
+
+    let response = next.run(request).instrument(span.clone()).await;
+    let status = response.status();
+    span.record("http.response.status_code", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    response
+}
+
+/// Add the trace context of the current span to an outgoing request, so the server
+/// called can join the trace
+pub fn inject_trace_context(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let context = Span::current().context();
+    let mut injector = HeaderInjector::default();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut injector));
+    injector.0.into_iter().fold(request, |request, (name, value)| request.header(name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use opentelemetry::trace::TraceContextExt;
+
+    #[test]
+    fn test_trace_context_round_trips_through_headers() {
+        let propagator = TraceContextPropagator::new();
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+        let mut headers = HeaderMap::new();
+        headers.insert("traceparent", HeaderValue::from_static(traceparent));
+        let context = opentelemetry::propagation::TextMapPropagator::extract(&propagator, &HeaderExtractor(&headers));
+        assert_eq!(
+            context.span().span_context().trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+
+        let mut injector = HeaderInjector::default();
+        opentelemetry::propagation::TextMapPropagator::inject_context(&propagator, &context, &mut injector);
+        assert_eq!(injector.0.get("traceparent").map(String::as_str), Some(traceparent));
+    }
+}
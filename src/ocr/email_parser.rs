@@ -0,0 +1,598 @@
+//! Email parsing for `.eml` (RFC 5322 / MIME) and Outlook `.msg` files.
+//!
+//! Only what ingestion needs is decoded: the envelope headers, the body as
+//! plain text (falling back to stripped HTML) and attachments. Outlook files
+//! are converted to MIME first with `msgconvert` (libemail-outlook-message-perl).
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::services::converter_sandbox::{ConverterError, SandboxedCommand};
+
+pub const EML_MIME_TYPE: &str = "message/rfc822";
+pub const MSG_MIME_TYPE: &str = "application/vnd.ms-outlook";
+
+/// Nested multiparts deeper than this are ignored
+const MAX_MIME_DEPTH: usize = 10;
+
+static ENCODED_WORD: Lazy<Regex> = Lazy::new(|| Regex::new(r"=\?([^?\s]+)\?([bBqQ])\?([^?\s]*)\?=").unwrap());
+static HTML_DROPPED_BLOCKS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<(script|style|head)[^>]*>.*?</(script|style|head)>").unwrap());
+static HTML_LINE_BREAKS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<br\s*/?>|</(p|div|tr|li|h[1-6])>").unwrap());
+static HTML_TAGS: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+static BLANK_LINES: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n\s*\n(\s*\n)+").unwrap());
+
+#[derive(Debug, Clone, Default)]
+pub struct ParsedEmail {
+    pub subject: Option<String>,
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub date: Option<DateTime<Utc>>,
+    pub message_id: Option<String>,
+    pub body_text: String,
+    pub attachments: Vec<EmailAttachment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailAttachment {
+    pub filename: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Email MIME type for a filename, by extension
+pub fn email_mime_type(filename: &str) -> Option<&'static str> {
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())?
+        .to_lowercase();
+    match extension.as_str() {
+        "eml" => Some(EML_MIME_TYPE),
+        "msg" => Some(MSG_MIME_TYPE),
+        _ => None,
+    }
+}
+
+pub fn is_email(mime_type: &str, filename: &str) -> bool {
+    mime_type == EML_MIME_TYPE || mime_type == MSG_MIME_TYPE || email_mime_type(filename).is_some()
+}
+
+/// Parse an `.eml` or `.msg` file
+pub async fn load_email(data: &[u8], mime_type: &str, filename: &str) -> Result<ParsedEmail> {
+    if mime_type == MSG_MIME_TYPE || email_mime_type(filename) == Some(MSG_MIME_TYPE) {
+        parse_email(&msg_to_eml(data).await?)
+    } else {
+        parse_email(data)
+    }
+}
+
+/// Convert an Outlook `.msg` file to MIME with `msgconvert`
+async fn msg_to_eml(data: &[u8]) -> Result<Vec<u8>> {
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let id = Uuid::new_v4();
+    let input = PathBuf::from(&temp_dir).join(format!("email_{}.msg", id));
+    let output = PathBuf::from(&temp_dir).join(format!("email_{}.eml", id));
+    tokio::fs::write(&input, data).await?;
+
+    let result = SandboxedCommand::new("msgconvert")
+        .arg("--outfile")
+        .arg(&output)
+        .arg(&input)
+        .input(data)
+        .output()
+        .await;
+    let _ = tokio::fs::remove_file(&input).await;
+
+    let converted = match result {
+        Ok(status) if status.status.success() => tokio::fs::read(&output).await.map_err(Into::into),
+        Ok(status) => Err(anyhow!(
+            "msgconvert failed: {}",
+            String::from_utf8_lossy(&status.stderr).trim()
+        )),
+        Err(e @ ConverterError::Spawn { .. }) => {
+            Err(anyhow!("{} (install libemail-outlook-message-perl)", e))
+        }
+        Err(e) => Err(e.into()),
+    };
+    let _ = tokio::fs::remove_file(&output).await;
+    converted
+}
+
+/// Parse a MIME message
+pub fn parse_email(raw: &[u8]) -> Result<ParsedEmail> {
+    let (headers, body) = split_headers(raw);
+    if headers.is_empty() {
+        return Err(anyhow!("Not an email: no headers found"));
+    }
+
+    let mut email = ParsedEmail {
+        subject: header(&headers, "subject").map(decode_words),
+        from: header(&headers, "from").map(decode_words),
+        to: header(&headers, "to").map(address_list).unwrap_or_default(),
+        cc: header(&headers, "cc").map(address_list).unwrap_or_default(),
+        date: header(&headers, "date")
+            .and_then(|d| DateTime::parse_from_rfc2822(d.trim()).ok())
+            .map(|d| d.with_timezone(&Utc)),
+        message_id: header(&headers, "message-id").map(|id| id.trim().to_string()),
+        ..Default::default()
+    };
+
+    let mut bodies = Bodies::default();
+    walk_part(&headers, body, &mut bodies, &mut email.attachments, 0);
+    email.body_text = match (bodies.plain, bodies.html) {
+        (Some(plain), _) => plain.trim().to_string(),
+        (None, Some(html)) => html_to_text(&html),
+        (None, None) => String::new(),
+    };
+
+    Ok(email)
+}
+
+impl ParsedEmail {
+    /// Searchable text: the envelope headers followed by the body
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if let Some(from) = &self.from {
+            text.push_str(&format!("From: {}\n", from));
+        }
+        if !self.to.is_empty() {
+            text.push_str(&format!("To: {}\n", self.to.join(", ")));
+        }
+        if !self.cc.is_empty() {
+            text.push_str(&format!("Cc: {}\n", self.cc.join(", ")));
+        }
+        if let Some(date) = &self.date {
+            text.push_str(&format!("Date: {}\n", date.to_rfc2822()));
+        }
+        if let Some(subject) = &self.subject {
+            text.push_str(&format!("Subject: {}\n", subject));
+        }
+        text.push('\n');
+        text.push_str(&self.body_text);
+        if !self.attachments.is_empty() {
+            let names: Vec<&str> = self.attachments.iter().map(|a| a.filename.as_str()).collect();
+            text.push_str(&format!("\n\nAttachments: {}", names.join(", ")));
+        }
+        text
+    }
+
+    /// Header fields for the document's metadata
+    pub fn metadata(&self) -> Map<String, Value> {
+        let mut metadata = Map::new();
+        if let Some(from) = &self.from {
+            metadata.insert("email_from".to_string(), Value::String(from.clone()));
+        }
+        if !self.to.is_empty() {
+            metadata.insert("email_to".to_string(), Value::from(self.to.clone()));
+        }
+        if !self.cc.is_empty() {
+            metadata.insert("email_cc".to_string(), Value::from(self.cc.clone()));
+        }
+        if let Some(subject) = &self.subject {
+            metadata.insert("email_subject".to_string(), Value::String(subject.clone()));
+        }
+        if let Some(date) = &self.date {
+            metadata.insert("email_date".to_string(), Value::String(date.to_rfc3339()));
+        }
+        if let Some(message_id) = &self.message_id {
+            metadata.insert("email_message_id".to_string(), Value::String(message_id.clone()));
+        }
+        metadata.insert("email_attachment_count".to_string(), Value::from(self.attachments.len()));
+        metadata
+    }
+}
+
+#[derive(Default)]
+struct Bodies {
+    plain: Option<String>,
+    html: Option<String>,
+}
+
+type Headers = Vec<(String, String)>;
+
+fn walk_part(headers: &Headers, body: &[u8], bodies: &mut Bodies, attachments: &mut Vec<EmailAttachment>, depth: usize) {
+    if depth > MAX_MIME_DEPTH {
+        return;
+    }
+
+    let (content_type, type_params) = header(headers, "content-type")
+        .map(parse_parameterized)
+        .unwrap_or_else(|| ("text/plain".to_string(), HashMap::new()));
+
+    if content_type.starts_with("multipart/") {
+        if let Some(boundary) = type_params.get("boundary") {
+            for part in split_multipart(body, boundary) {
+                let (part_headers, part_body) = split_headers(part);
+                walk_part(&part_headers, part_body, bodies, attachments, depth + 1);
+            }
+        }
+        return;
+    }
+
+    let (disposition, disposition_params) = header(headers, "content-disposition")
+        .map(parse_parameterized)
+        .unwrap_or_default();
+    let filename = disposition_params
+        .get("filename")
+        .or_else(|| type_params.get("name"))
+        .map(|name| decode_words(name));
+    let data = decode_transfer_encoding(header(headers, "content-transfer-encoding").unwrap_or("7bit"), body);
+
+    let is_attachment = disposition == "attachment"
+        || content_type == EML_MIME_TYPE
+        || (filename.is_some() && disposition != "inline");
+
+    if is_attachment {
+        let filename = filename.unwrap_or_else(|| {
+            let extension = if content_type == EML_MIME_TYPE {
+                "eml"
+            } else {
+                mime_guess::get_mime_extensions_str(&content_type)
+                    .and_then(|extensions| extensions.first())
+                    .copied()
+                    .unwrap_or("bin")
+            };
+            format!("attachment-{}.{}", attachments.len() + 1, extension)
+        });
+        let mime_type = if content_type == "application/octet-stream" {
+            mime_guess::from_path(&filename).first_or_octet_stream().to_string()
+        } else {
+            content_type
+        };
+        attachments.push(EmailAttachment { filename, mime_type, data });
+        return;
+    }
+
+    let charset = type_params.get("charset").map(String::as_str).unwrap_or("utf-8");
+    match content_type.as_str() {
+        "text/plain" if bodies.plain.is_none() => bodies.plain = Some(decode_charset(&data, charset)),
+        "text/html" if bodies.html.is_none() => bodies.html = Some(decode_charset(&data, charset)),
+        _ => {}
+    }
+}
+
+/// Split a message or part into unfolded headers and the body
+fn split_headers(raw: &[u8]) -> (Headers, &[u8]) {
+    // A part that starts with a blank line has no headers
+    if let Some(body) = raw.strip_prefix(b"\r\n").or_else(|| raw.strip_prefix(b"\n")) {
+        return (Vec::new(), body);
+    }
+
+    let (header_end, body_start) = find_subslice(raw, b"\r\n\r\n")
+        .map(|i| (i, i + 4))
+        .or_else(|| find_subslice(raw, b"\n\n").map(|i| (i, i + 2)))
+        .unwrap_or((raw.len(), raw.len()));
+
+    let header_text = String::from_utf8_lossy(&raw[..header_end]);
+    let mut headers: Headers = Vec::new();
+    for line in header_text.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    (headers, &raw[body_start.min(raw.len())..])
+}
+
+fn header<'a>(headers: &'a Headers, name: &str) -> Option<&'a str> {
+    headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+}
+
+/// `text/plain; charset="utf-8"` -> (`text/plain`, {charset: utf-8}).
+/// RFC 2231 `filename*=utf-8''...` values are percent-decoded.
+fn parse_parameterized(value: &str) -> (String, HashMap<String, String>) {
+    let mut segments = split_outside_quotes(value, ';').into_iter();
+    let main = segments.next().unwrap_or_default().trim().to_lowercase();
+
+    let mut params = HashMap::new();
+    for segment in segments {
+        let Some((key, value)) = segment.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().trim_matches('"').to_string();
+        match key.strip_suffix('*') {
+            Some(key) => {
+                let encoded = value.splitn(3, '\'').nth(2).unwrap_or(&value);
+                params.insert(key.to_string(), percent_decode(encoded));
+            }
+            None => {
+                params.insert(key, value);
+            }
+        }
+    }
+
+    (main, params)
+}
+
+fn split_outside_quotes(value: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut in_angle = false;
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_angle = true,
+            '>' if !in_quotes => in_angle = false,
+            c if c == separator && !in_quotes && !in_angle => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+fn address_list(value: &str) -> Vec<String> {
+    split_outside_quotes(value, ',')
+        .into_iter()
+        .map(|address| decode_words(address.trim()))
+        .filter(|address| !address.is_empty())
+        .collect()
+}
+
+/// Body parts between `--boundary` delimiter lines
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut part_start: Option<usize> = None;
+    let mut position = 0;
+
+    while position < body.len() {
+        let line_end = body[position..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map(|i| position + i + 1)
+            .unwrap_or(body.len());
+        let line = body[position..line_end].trim_ascii_end();
+
+        if let Some(rest) = line.strip_prefix(delimiter.as_bytes()) {
+            let closing = rest.trim_ascii() == b"--";
+            if closing || rest.trim_ascii().is_empty() {
+                if let Some(start) = part_start.take() {
+                    parts.push(trim_line_break(&body[start..position]));
+                }
+                if closing {
+                    break;
+                }
+                part_start = Some(line_end);
+            }
+        }
+        position = line_end;
+    }
+
+    parts
+}
+
+fn trim_line_break(part: &[u8]) -> &[u8] {
+    let part = part.strip_suffix(b"\n").unwrap_or(part);
+    part.strip_suffix(b"\r").unwrap_or(part)
+}
+
+fn decode_transfer_encoding(encoding: &str, body: &[u8]) -> Vec<u8> {
+    match encoding.trim().to_lowercase().as_str() {
+        "base64" => decode_base64(body),
+        "quoted-printable" => decode_quoted_printable(body, false),
+        _ => body.to_vec(),
+    }
+}
+
+fn decode_base64(input: &[u8]) -> Vec<u8> {
+    fn value(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            b'+' | b'-' => Some(62),
+            b'/' | b'_' => Some(63),
+            _ => None,
+        }
+    }
+
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &c in input {
+        if c == b'=' {
+            break;
+        }
+        let Some(v) = value(c) else {
+            continue;
+        };
+        buffer = (buffer << 6) | v;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    output
+}
+
+/// Quoted-printable; in headers (`q_encoding`) an underscore is a space
+fn decode_quoted_printable(input: &[u8], q_encoding: bool) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'=' => {
+                let next = &input[i + 1..];
+                if next.starts_with(b"\r\n") {
+                    i += 3;
+                } else if next.starts_with(b"\n") {
+                    i += 2;
+                } else if let Some(byte) = next
+                    .get(..2)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    output.push(byte);
+                    i += 3;
+                } else {
+                    output.push(b'=');
+                    i += 1;
+                }
+            }
+            b'_' if q_encoding => {
+                output.push(b' ');
+                i += 1;
+            }
+            byte => {
+                output.push(byte);
+                i += 1;
+            }
+        }
+    }
+    output
+}
+
+fn decode_charset(data: &[u8], charset: &str) -> String {
+    match charset.trim().to_lowercase().as_str() {
+        // Latin-1 maps bytes to code points directly; close enough for windows-1252
+        "iso-8859-1" | "latin1" | "windows-1252" | "cp1252" => data.iter().map(|&b| b as char).collect(),
+        _ => String::from_utf8_lossy(data).to_string(),
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                output.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        output.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&output).to_string()
+}
+
+/// Decode RFC 2047 encoded words; whitespace between adjacent encoded words is dropped
+fn decode_words(value: &str) -> String {
+    let mut output = String::new();
+    let mut last_end = 0;
+    let mut previous_was_word = false;
+
+    for captures in ENCODED_WORD.captures_iter(value) {
+        let whole = captures.get(0).unwrap();
+        let gap = &value[last_end..whole.start()];
+        if !(previous_was_word && gap.trim().is_empty()) {
+            output.push_str(gap);
+        }
+
+        let encoded = captures[3].as_bytes();
+        let bytes = if captures[2].eq_ignore_ascii_case("b") {
+            decode_base64(encoded)
+        } else {
+            decode_quoted_printable(encoded, true)
+        };
+        output.push_str(&decode_charset(&bytes, &captures[1]));
+
+        last_end = whole.end();
+        previous_was_word = true;
+    }
+    output.push_str(&value[last_end..]);
+    output.trim().to_string()
+}
+
+fn html_to_text(html: &str) -> String {
+    let text = HTML_DROPPED_BLOCKS.replace_all(html, "");
+    let text = HTML_LINE_BREAKS.replace_all(&text, "\n");
+    let text = HTML_TAGS.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    BLANK_LINES.replace_all(lines.join("\n").trim(), "\n\n").to_string()
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTIPART_EMAIL: &str = "From: =?UTF-8?B?SsO2cmc=?= <jorg@example.com>\r\n\
+To: scans@example.com, \"Doe, Jane\" <jane@example.com>\r\n\
+Subject: =?utf-8?Q?Invoice_M=C3=A4rz?=\r\n\t2024\r\n\
+Date: Tue, 5 Mar 2024 10:15:00 +0100\r\n\
+Message-ID: <abc@example.com>\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n\
+\r\n\
+This is a multi-part message.\r\n\
+--XYZ\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+Please find the invoice attached. Total =E2=82=AC 12,50\r\n\
+--XYZ\r\n\
+Content-Type: application/pdf; name=\"invoice.pdf\"\r\n\
+Content-Disposition: attachment; filename=\"invoice.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0x\r\n\
+LjQ=\r\n\
+--XYZ--\r\n";
+
+    #[test]
+    fn test_parses_headers_body_and_attachments() {
+        let email = parse_email(MULTIPART_EMAIL.as_bytes()).unwrap();
+
+        assert_eq!(email.from.as_deref(), Some("Jörg <jorg@example.com>"));
+        assert_eq!(email.to, vec!["scans@example.com", "\"Doe, Jane\" <jane@example.com>"]);
+        assert_eq!(email.subject.as_deref(), Some("Invoice März 2024"));
+        assert_eq!(email.message_id.as_deref(), Some("<abc@example.com>"));
+        assert_eq!(email.date.unwrap().to_rfc3339(), "2024-03-05T09:15:00+00:00");
+        assert_eq!(email.body_text, "Please find the invoice attached. Total € 12,50");
+
+        assert_eq!(email.attachments.len(), 1);
+        assert_eq!(email.attachments[0].filename, "invoice.pdf");
+        assert_eq!(email.attachments[0].mime_type, "application/pdf");
+        assert_eq!(email.attachments[0].data, b"%PDF-1.4");
+    }
+
+    #[test]
+    fn test_html_only_body_is_converted_to_text() {
+        let raw = "Subject: Hi\nContent-Type: text/html\n\n<html><head><style>p{}</style></head>\
+                   <body><p>Hello&nbsp;there</p><p>Second &amp; last</p></body></html>";
+        let email = parse_email(raw.as_bytes()).unwrap();
+        assert_eq!(email.body_text, "Hello there\nSecond & last");
+        assert!(email.attachments.is_empty());
+    }
+
+    #[test]
+    fn test_email_detection_by_extension() {
+        assert_eq!(email_mime_type("Mail.EML"), Some(EML_MIME_TYPE));
+        assert_eq!(email_mime_type("mail.msg"), Some(MSG_MIME_TYPE));
+        assert!(!is_email("application/pdf", "scan.pdf"));
+        assert!(is_email("application/octet-stream", "message.msg"));
+    }
+}
@@ -16,7 +16,14 @@ use tesseract::{Tesseract, PageSegMode, OcrEngineMode};
 
 use crate::models::Settings;
 use crate::services::file_service::FileService;
+use super::email_parser;
+#[cfg(feature = "ocr")]
+use super::error::OcrError;
 use super::xml_extractor::XmlOfficeExtractor;
+#[cfg(feature = "ocr")]
+use super::OCRMYPDF_TIMEOUT;
+#[cfg(feature = "ocr")]
+use crate::services::converter_sandbox::{hash_file, ConverterError, SandboxedCommand};
 // Removed text_sanitization import - now using minimal inline sanitization
 
 /// RAII guard for automatic cleanup of temporary files
@@ -52,6 +59,15 @@ pub struct ImageQualityStats {
     pub sharpness: f32,
 }
 
+/// How a page image is turned upright before OCR
+#[cfg(feature = "ocr")]
+enum Orientation {
+    /// The page's recorded transform, None when it is left as it is
+    Recorded(Option<crate::models::PageTransform>),
+    /// No transforms are recorded for the document; guess from the image's shape
+    Guess,
+}
+
 #[derive(Debug, Clone)]
 pub struct OcrResult {
     pub text: String,
@@ -71,6 +87,11 @@ impl EnhancedOcrService {
     // Security limits for Office document processing
     const MAX_OFFICE_DOCUMENT_SIZE: u64 = 100 * 1024 * 1024; // 100MB for all Office documents
     const MAX_ENTRY_NAME_LENGTH: usize = 255; // Maximum length of entry names
+    // Pages of a PDF with fewer words in their text layer are treated as scans
+    #[cfg(feature = "ocr")]
+    const MIN_TEXT_LAYER_WORDS: usize = 3;
+    #[cfg(feature = "ocr")]
+    const PAGE_OCR_DPI: u32 = 300;
 
     /// Remove null bytes from text to prevent PostgreSQL errors
     /// This is the ONLY sanitization we do - preserving all other original content
@@ -101,6 +122,15 @@ impl EnhancedOcrService {
     /// Extract text from image with high-quality OCR settings
     #[cfg(feature = "ocr")]
     pub async fn extract_text_from_image(&self, file_path: &str, settings: &Settings) -> Result<OcrResult> {
+        let orientation = match super::page_transforms::PageTransforms::current() {
+            Some(transforms) => Orientation::Recorded(transforms.get(1).cloned()),
+            None => Orientation::Guess,
+        };
+        self.extract_text_from_page(file_path, settings, &orientation).await
+    }
+
+    #[cfg(feature = "ocr")]
+    async fn extract_text_from_page(&self, file_path: &str, settings: &Settings, orientation: &Orientation) -> Result<OcrResult> {
         let start_time = std::time::Instant::now();
         info!("Starting enhanced OCR for image: {}", file_path);
         
@@ -108,8 +138,10 @@ impl EnhancedOcrService {
         
         // Load and preprocess the image
         let (processed_image_path, preprocess_steps) = if settings.enable_image_preprocessing {
-            let (processed_path, steps) = self.preprocess_image(file_path, settings).await?;
+            let (processed_path, steps) = self.preprocess_image(file_path, settings, orientation).await?;
             (processed_path, steps)
+        } else if let Orientation::Recorded(Some(transform)) = orientation {
+            self.transform_image(file_path, transform).await?
         } else {
             (file_path.to_string(), Vec::new())
         };
@@ -171,9 +203,25 @@ impl EnhancedOcrService {
         Ok(result)
     }
 
+    /// Only turn an image upright, for OCR without preprocessing
+    #[cfg(feature = "ocr")]
+    async fn transform_image(&self, input_path: &str, transform: &crate::models::PageTransform) -> Result<(String, Vec<String>)> {
+        let resolved_path = self.resolve_file_path(input_path).await?;
+        let img = super::page_transforms::apply_transform(&image::open(&resolved_path)?, transform);
+
+        let temp_path = format!("{}/transformed_{}.png", self.temp_dir, uuid::Uuid::new_v4());
+        img.save(&temp_path)?;
+        Ok((temp_path, vec![Self::transform_step(transform)]))
+    }
+
+    #[cfg(feature = "ocr")]
+    fn transform_step(transform: &crate::models::PageTransform) -> String {
+        format!("Page transform (rotation {}°, deskew {:.1}°)", transform.rotation, transform.skew_degrees)
+    }
+
     /// Preprocess image for optimal OCR quality, especially for challenging conditions
     #[cfg(feature = "ocr")]
-    async fn preprocess_image(&self, input_path: &str, settings: &Settings) -> Result<(String, Vec<String>)> {
+    async fn preprocess_image(&self, input_path: &str, settings: &Settings, orientation: &Orientation) -> Result<(String, Vec<String>)> {
         // Resolve the file path first
         let resolved_path = self.resolve_file_path(input_path).await?;
         let img = image::open(&resolved_path)?;
@@ -182,9 +230,18 @@ impl EnhancedOcrService {
         
         info!("Original image dimensions: {}x{}", processed_img.width(), processed_img.height());
         
-        // Apply orientation detection and correction
-        if settings.ocr_detect_orientation {
-            processed_img = self.detect_and_correct_orientation(processed_img)?;
+        // Turn the page upright as recorded, or guess when nothing is recorded
+        match orientation {
+            Orientation::Recorded(Some(transform)) => {
+                processed_img = super::page_transforms::apply_transform(&processed_img, transform);
+                preprocessing_applied.push(Self::transform_step(transform));
+            }
+            Orientation::Recorded(None) => {}
+            Orientation::Guess => {
+                if settings.ocr_detect_orientation {
+                    processed_img = self.detect_and_correct_orientation(processed_img)?;
+                }
+            }
         }
         
         // Aggressively upscale low-resolution images for better OCR
@@ -910,6 +967,14 @@ impl EnhancedOcrService {
             ));
         }
         
+        // PDFs mixing a text layer with scanned pages keep the text and OCR only the scans
+        match self.extract_text_from_mixed_pdf(file_path, settings, start_time).await {
+            Ok(Some(result)) => return Ok(result),
+            Ok(None) => {}
+            Err(e) if matches!(e.downcast_ref::<OcrError>(), Some(OcrError::Interrupted)) => return Err(e),
+            Err(e) => warn!("Page-level extraction failed for '{}': {}, treating the PDF as a whole", file_path, e),
+        }
+
         // First try to extract text without OCR for performance (using --skip-text)
         let quick_extraction_result = self.extract_pdf_text_quick(file_path).await;
         
@@ -978,6 +1043,109 @@ impl EnhancedOcrService {
         full_ocr_result
     }
     
+    /// Text of a PDF whose pages are partly text and partly scans: pages with a text layer
+    /// keep their text, the others are rendered and OCRed one by one, and the results are
+    /// merged in page order. Under an OCR queue job, long scans and scans with turned pages
+    /// are OCRed page by page as well, each page with its own transform, and every page is
+    /// checkpointed so a shutdown interrupts the job between pages.
+    /// None when no page, or every page of a short scan, has a text layer, or when
+    /// `PDF_PAGE_LEVEL_OCR` is off; those PDFs are handled as a whole.
+    #[cfg(feature = "ocr")]
+    async fn extract_text_from_mixed_pdf(&self, file_path: &str, settings: &Settings, start_time: std::time::Instant) -> Result<Option<OcrResult>> {
+        use super::{page_checkpoints, page_images, page_transforms, pdf_pages};
+
+        if !Self::page_level_ocr_enabled() {
+            return Ok(None);
+        }
+
+        let mut pages = pdf_pages::page_texts(std::path::Path::new(file_path)).await?;
+        let scanned_pages = pdf_pages::pages_without_text(&pages, Self::MIN_TEXT_LAYER_WORDS);
+        let checkpoints = page_checkpoints::PageCheckpoints::current();
+        let long_scan = checkpoints.is_some() && pages.len() >= page_checkpoints::min_pages();
+        let transforms = page_transforms::PageTransforms::current();
+        let turned = transforms.as_ref().is_some_and(|t| t.any());
+        if scanned_pages.is_empty() || (scanned_pages.len() == pages.len() && !long_scan && !turned) {
+            return Ok(None);
+        }
+        info!(
+            "PDF '{}' has {} of {} pages without a text layer, OCRing only those",
+            file_path,
+            scanned_pages.len(),
+            pages.len()
+        );
+
+        let done = match &checkpoints {
+            Some(checkpoints) => checkpoints.load().await,
+            None => Default::default(),
+        };
+        let data = tokio::fs::read(file_path).await?;
+        let mut page_confidences = Vec::with_capacity(scanned_pages.len());
+        for &page in &scanned_pages {
+            if let Some((text, confidence)) = done.get(&(page as i32)) {
+                pages[page as usize - 1] = text.clone();
+                page_confidences.push(*confidence);
+                continue;
+            }
+            if checkpoints.is_some() && crate::scheduling::shutdown::requested() {
+                info!("Stopping OCR of '{}' before page {} for shutdown", file_path, page);
+                return Err(OcrError::Interrupted.into());
+            }
+
+            let rendered = page_images::render_page_range(&data, "application/pdf", Self::PAGE_OCR_DPI, page, page).await?;
+            let Some(image) = rendered.first() else {
+                warn!("Page {} of '{}' could not be rendered for OCR", page, file_path);
+                continue;
+            };
+
+            let image_path = format!("{}/mixed_page_{}_{}.png", self.temp_dir, uuid::Uuid::new_v4(), page);
+            let _cleanup = FileCleanupGuard::new(&image_path);
+            image.save(&image_path)?;
+
+            let orientation = match &transforms {
+                Some(transforms) => Orientation::Recorded(transforms.get(page as i32).cloned()),
+                None => Orientation::Guess,
+            };
+            let result = self.extract_text_from_page(&image_path, settings, &orientation).await?;
+            if let Some(processed_path) = &result.processed_image_path {
+                let _ = tokio::fs::remove_file(processed_path).await;
+            }
+            if let Some(checkpoints) = &checkpoints {
+                checkpoints.save(page as i32, &result.text, result.confidence).await;
+            }
+            pages[page as usize - 1] = result.text;
+            page_confidences.push(result.confidence);
+        }
+
+        // Text layer pages count with the confidence of plain text extraction
+        let text_pages = pages.len() - scanned_pages.len();
+        let confidence = (95.0 * text_pages as f32 + page_confidences.iter().sum::<f32>())
+            / (text_pages + page_confidences.len()).max(1) as f32;
+
+        let text = pages.iter().filter(|page| !page.is_empty()).cloned().collect::<Vec<_>>().join("\n\n");
+        let text = Self::remove_null_bytes(&text);
+        let word_count = self.count_words_safely(&text);
+
+        Ok(Some(OcrResult {
+            text,
+            confidence,
+            processing_time_ms: start_time.elapsed().as_millis() as u64,
+            word_count,
+            preprocessing_applied: vec![
+                format!("PDF text extraction (pdftotext, {} pages)", text_pages),
+                format!("Page OCR (tesseract, pages {:?})", scanned_pages),
+            ],
+            processed_image_path: None,
+        }))
+    }
+
+    /// Whether mixed PDFs are OCRed page by page, from `PDF_PAGE_LEVEL_OCR`
+    #[cfg(feature = "ocr")]
+    fn page_level_ocr_enabled() -> bool {
+        std::env::var("PDF_PAGE_LEVEL_OCR")
+            .map(|v| !matches!(v.to_lowercase().as_str(), "false" | "0" | "no"))
+            .unwrap_or(true)
+    }
+
     /// Assess if text extraction quality is sufficient or if OCR fallback is needed
     #[cfg(feature = "ocr")]
     fn is_text_extraction_quality_sufficient(&self, text: &str, word_count: usize, file_size: u64) -> bool {
@@ -1059,64 +1227,40 @@ impl EnhancedOcrService {
         let temp_ocr_path = format!("{}/{}", self.temp_dir, temp_ocr_filename);
         
         // Run ocrmypdf with progressive fallback strategies
-        let ocrmypdf_result = tokio::time::timeout(
-            std::time::Duration::from_secs(300), // 5 minute timeout for OCR
-            tokio::task::spawn_blocking({
-                let file_path = file_path.to_string();
-                let temp_ocr_path = temp_ocr_path.clone();
-                move || {
-                    // Strategy 1: Standard OCR with cleaning
-                    let mut result = std::process::Command::new("ocrmypdf")
-                        .arg("--force-ocr")  // OCR even if text is detected
-                        .arg("-O2")          // Optimize level 2 (balanced quality/speed)
-                        .arg("--deskew")     // Correct skewed pages
-                        .arg("--clean")      // Clean up artifacts
-                        .arg("--language")
-                        .arg("eng")          // English language
-                        .arg(&file_path)
-                        .arg(&temp_ocr_path)
-                        .output();
-                    
-                    if result.is_ok() && result.as_ref().unwrap().status.success() {
-                        return result;
-                    }
-                    
-                    // Strategy 2: If standard OCR fails, try with error recovery
-                    eprintln!("Standard OCR failed, trying recovery mode...");
-                    result = std::process::Command::new("ocrmypdf")
-                        .arg("--force-ocr")
-                        .arg("--fix-metadata")  // Fix metadata issues
-                        .arg("--remove-background")  // Remove background noise
-                        .arg("-O1")          // Lower optimization for problematic PDFs
-                        .arg("--language")
-                        .arg("eng")
-                        .arg(&file_path)
-                        .arg(&temp_ocr_path)
-                        .output();
-                    
-                    if result.is_ok() && result.as_ref().unwrap().status.success() {
-                        return result;
-                    }
-                    
-                    // Strategy 3: Last resort - minimal processing (skips very large pages)
-                    eprintln!("Recovery mode failed, trying minimal processing...");
-                    std::process::Command::new("ocrmypdf")
-                        .arg("--force-ocr")
-                        .arg("--skip-big")  // Skip very large pages that might cause memory issues
-                        .arg("--language")
-                        .arg("eng")
-                        .arg(&file_path)
-                        .arg(&temp_ocr_path)
-                        .output()
-                }
-            })
-        ).await;
-        
-        let ocrmypdf_output = match ocrmypdf_result {
-            Ok(Ok(output)) => output?,
-            Ok(Err(e)) => return Err(anyhow!("Failed to join ocrmypdf task: {}", e)),
-            Err(_) => return Err(anyhow!("ocrmypdf timed out after 5 minutes for file '{}'", file_path)),
+        let file_hash = hash_file(std::path::Path::new(file_path)).await.ok();
+        let ocrmypdf = |options: &[&str]| {
+            let mut command = SandboxedCommand::new("ocrmypdf")
+                .args(options)
+                .arg(file_path)
+                .arg(&temp_ocr_path)
+                .timeout(OCRMYPDF_TIMEOUT);
+            if let Some(file_hash) = &file_hash {
+                command = command.input_hash(file_hash.clone());
+            }
+            command.output()
+        };
+        // A run killed by the sandbox is not retried, so one bad file counts a single strike
+        let failed = |result: &Result<std::process::Output, ConverterError>| {
+            matches!(result, Ok(output) if !output.status.success())
         };
+
+        // Strategy 1: Standard OCR with cleaning
+        let mut ocrmypdf_result = ocrmypdf(&["--force-ocr", "-O2", "--deskew", "--clean", "--language", "eng"]).await;
+
+        // Strategy 2: If standard OCR fails, try with error recovery
+        if failed(&ocrmypdf_result) {
+            warn!("Standard OCR failed, trying recovery mode...");
+            ocrmypdf_result =
+                ocrmypdf(&["--force-ocr", "--fix-metadata", "--remove-background", "-O1", "--language", "eng"]).await;
+        }
+
+        // Strategy 3: Last resort - minimal processing (skips very large pages)
+        if failed(&ocrmypdf_result) {
+            warn!("Recovery mode failed, trying minimal processing...");
+            ocrmypdf_result = ocrmypdf(&["--force-ocr", "--skip-big", "--language", "eng"]).await;
+        }
+
+        let ocrmypdf_output = ocrmypdf_result.map_err(|e| anyhow!("ocrmypdf failed for '{}': {}", file_path, e))?;
         
         if !ocrmypdf_output.status.success() {
             let stderr = String::from_utf8_lossy(&ocrmypdf_output.stderr);
@@ -1127,37 +1271,30 @@ impl EnhancedOcrService {
             ));
         }
         
-        // Extract text from the OCR'd PDF
-        let ocr_text_result = tokio::task::spawn_blocking({
-            let temp_ocr_path = temp_ocr_path.clone();
-            move || -> Result<String> {
-                let _bytes = std::fs::read(&temp_ocr_path)?;
-                // Catch panics from pdf-extract library (same pattern as used elsewhere)
-                // Extract text from the OCR'd PDF using ocrmypdf's sidecar option
-                let temp_text_path = format!("{}.txt", temp_ocr_path);
-                let extract_result = std::process::Command::new("ocrmypdf")
-                    .arg("--sidecar")  // Extract text to a sidecar file
-                    .arg(&temp_text_path)
-                    .arg(&temp_ocr_path)
-                    .arg("-")  // Output to stdout (dummy, required by ocrmypdf)
-                    .output()?;
-                
-                if !extract_result.status.success() {
-                    let stderr = String::from_utf8_lossy(&extract_result.stderr);
-                    return Err(anyhow!(
-                        "ocrmypdf text extraction failed: {}",
-                        stderr
-                    ));
-                }
-                
-                // Read the extracted text from the sidecar file
-                let text = std::fs::read_to_string(&temp_text_path)?;
-                
-                // Clean up the text file
-                let _ = std::fs::remove_file(&temp_text_path);
-                Ok(text.trim().to_string())
-            }
-        }).await??;
+        // Extract text from the OCR'd PDF using ocrmypdf's sidecar option
+        let temp_text_path = format!("{}.txt", temp_ocr_path);
+        let extract_result = SandboxedCommand::new("ocrmypdf")
+            .arg("--sidecar")  // Extract text to a sidecar file
+            .arg(&temp_text_path)
+            .arg(&temp_ocr_path)
+            .arg("-")  // Output to stdout (dummy, required by ocrmypdf)
+            .timeout(OCRMYPDF_TIMEOUT)
+            .output()
+            .await?;
+
+        if !extract_result.status.success() {
+            let stderr = String::from_utf8_lossy(&extract_result.stderr);
+            return Err(anyhow!(
+                "ocrmypdf text extraction failed: {}",
+                stderr
+            ));
+        }
+
+        // Read the extracted text from the sidecar file
+        let ocr_text_result = tokio::fs::read_to_string(&temp_text_path).await?.trim().to_string();
+
+        // Clean up the text file
+        let _ = tokio::fs::remove_file(&temp_text_path).await;
         
         // Clean up temporary file
         let _ = tokio::fs::remove_file(&temp_ocr_path).await;
@@ -1196,7 +1333,15 @@ impl EnhancedOcrService {
         // Strategy 1: Fast text extraction using pdftotext (for existing text)
         debug!("Trying pdftotext for existing text extraction: {}", file_path);
         debug!("Using temp file path: {}", temp_text_path);
-        let pdftotext_result = tokio::process::Command::new("pdftotext")
+        let file_hash = hash_file(std::path::Path::new(file_path)).await.ok();
+        let sandboxed = |program: &str| {
+            let command = SandboxedCommand::new(program);
+            match &file_hash {
+                Some(file_hash) => command.input_hash(file_hash.clone()),
+                None => command,
+            }
+        };
+        let pdftotext_result = sandboxed("pdftotext")
             .arg("-layout")  // Preserve layout
             .arg(file_path)
             .arg(&temp_text_path)
@@ -1258,11 +1403,12 @@ impl EnhancedOcrService {
         info!("Direct extraction insufficient for '{}', using OCR extraction", file_path);
         
         // Strategy 3: Use ocrmypdf --sidecar to extract existing OCR text
-        let ocrmypdf_result = tokio::process::Command::new("ocrmypdf")
+        let ocrmypdf_result = sandboxed("ocrmypdf")
             .arg("--sidecar")
             .arg(&temp_text_path)
             .arg(file_path)
             .arg("-")  // Dummy output (we only want sidecar)
+            .timeout(OCRMYPDF_TIMEOUT)
             .output()
             .await;
         
@@ -1516,6 +1662,24 @@ impl EnhancedOcrService {
         })
     }
 
+    /// Extract the envelope headers and body text of an email. Attachments are
+    /// ingested as separate documents after OCR completes.
+    pub async fn extract_text_from_email(&self, file_path: &str, mime_type: &str) -> Result<OcrResult> {
+        let start_time = std::time::Instant::now();
+        let data = tokio::fs::read(file_path).await?;
+        let email = email_parser::load_email(&data, mime_type, file_path).await?;
+        let text = Self::remove_null_bytes(&email.to_text()).trim().to_string();
+
+        Ok(OcrResult {
+            word_count: self.count_words_safely(&text),
+            text,
+            confidence: 100.0, // Email text is read, not recognized
+            processing_time_ms: start_time.elapsed().as_millis() as u64,
+            preprocessing_applied: vec!["Email parsing".to_string()],
+            processed_image_path: None,
+        })
+    }
+
     /// Extract text from any supported file type
     pub async fn extract_text(&self, file_path: &str, mime_type: &str, settings: &Settings) -> Result<OcrResult> {
         // Resolve the actual file path
@@ -1594,6 +1758,9 @@ impl EnhancedOcrService {
                 // extract_text_from_office now returns OcrResult directly
                 self.extract_text_from_office(&resolved_path, mime, settings).await
             }
+            mime if mime == email_parser::EML_MIME_TYPE || mime == email_parser::MSG_MIME_TYPE => {
+                self.extract_text_from_email(&resolved_path, mime).await
+            }
             _ => Err(anyhow::anyhow!("Unsupported file type: {}", mime_type)),
         }
     }
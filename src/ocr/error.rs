@@ -42,6 +42,9 @@ pub enum OcrError {
     #[error("Hardware acceleration not available: {details}")]
     HardwareAccelerationUnavailable { details: String },
     
+    #[error("OCR stopped between pages because the server is shutting down")]
+    Interrupted,
+    
     #[error(transparent)]
     Io(#[from] std::io::Error),
     
@@ -56,6 +59,7 @@ impl OcrError {
             OcrError::InsufficientMemory { .. }
                 | OcrError::OcrTimeout { .. }
                 | OcrError::LowConfidence { .. }
+                | OcrError::Interrupted
         )
     }
     
@@ -81,6 +85,7 @@ impl OcrError {
             OcrError::InitializationFailed { .. } => "OCR_INIT_FAILED",
             OcrError::LowConfidence { .. } => "OCR_LOW_CONFIDENCE",
             OcrError::HardwareAccelerationUnavailable { .. } => "OCR_NO_HW_ACCEL",
+            OcrError::Interrupted => "OCR_INTERRUPTED",
             OcrError::Io(_) => "OCR_IO_ERROR",
             OcrError::Other(_) => "OCR_UNKNOWN_ERROR",
         }
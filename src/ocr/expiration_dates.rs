@@ -0,0 +1,89 @@
+//! Rule-based detection of when a contract or certificate expires or renews.
+//!
+//! Looks for a label such as `Valid until`, `Expiration date` or `Renewal date` and
+//! their German counterparts, and takes the first date after it on the same line, or
+//! on the next line when the label ends its line.
+
+use chrono::NaiveDate;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::invoice_fields::parse_date;
+use crate::models::{EXPIRATION_KIND_EXPIRATION, EXPIRATION_KIND_RENEWAL};
+
+/// Most specific first, since `expires` also starts `expires on`
+const EXPIRATION_LABELS: &[&str] = &[
+    "expiration date", "expiry date", "date of expiry", "expires on", "expires", "valid until", "valid through",
+    "valid thru", "term ends", "terminates on", "end date", "ablaufdatum", "gültig bis", "läuft ab am",
+    "befristet bis", "vertragsende", "laufzeit bis",
+];
+const RENEWAL_LABELS: &[&str] = &[
+    "renewal date", "renews on", "renews automatically on", "next renewal", "verlängerung am",
+    "verlängert sich am", "verlängerungsdatum",
+];
+/// Characters after a label a date is looked for in
+const MAX_DISTANCE: usize = 60;
+
+static DATE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\b(\d{4}-\d{2}-\d{2}|\d{1,2}[./]\d{1,2}[./]\d{2,4}|[[:alpha:]]+\.? \d{1,2}, \d{4}|\d{1,2}\.? [[:alpha:]]+\.? \d{4})\b",
+    )
+    .unwrap()
+});
+
+/// The date a text says it expires or renews on, and which of the two it is
+pub fn find_expiration(text: &str) -> Option<(NaiveDate, &'static str)> {
+    let lower = text.to_lowercase();
+    let lines: Vec<&str> = lower.lines().collect();
+    lines.iter().enumerate().find_map(|(index, line)| {
+        let labelled = |labels: &[&str]| {
+            labels.iter().find_map(|label| {
+                let rest = &line[line.find(label)? + label.len()..];
+                let rest = rest.trim_start_matches(|c: char| c == ':' || c == '-' || c.is_whitespace());
+                let rest = if rest.is_empty() { *lines.get(index + 1)? } else { rest };
+                first_date(rest)
+            })
+        };
+        labelled(RENEWAL_LABELS)
+            .map(|date| (date, EXPIRATION_KIND_RENEWAL))
+            .or_else(|| labelled(EXPIRATION_LABELS).map(|date| (date, EXPIRATION_KIND_EXPIRATION)))
+    })
+}
+
+/// The first date near the start of a text
+fn first_date(text: &str) -> Option<NaiveDate> {
+    let end = text.char_indices().nth(MAX_DISTANCE).map_or(text.len(), |(index, _)| index);
+    DATE.find_iter(&text[..end])
+        .find_map(|found| parse_date(found.as_str().replace(". ", " ").trim_end_matches('.')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_find_expiration() {
+        let text = "Service Agreement\nThis agreement is valid until 31.12.2026 and may be cancelled.";
+        assert_eq!(find_expiration(text), Some((date(2026, 12, 31), EXPIRATION_KIND_EXPIRATION)));
+
+        let text = "Certificate No. 4711\nExpiration Date:\n2027-03-15\n";
+        assert_eq!(find_expiration(text), Some((date(2027, 3, 15), EXPIRATION_KIND_EXPIRATION)));
+
+        let text = "Subscription\nRenewal date: March 1, 2026";
+        assert_eq!(find_expiration(text), Some((date(2026, 3, 1), EXPIRATION_KIND_RENEWAL)));
+
+        let text = "Mietvertrag\nDer Vertrag ist befristet bis 30.06.2027.";
+        assert_eq!(find_expiration(text), Some((date(2027, 6, 30), EXPIRATION_KIND_EXPIRATION)));
+        assert_eq!(find_expiration("Gültig bis: 01.04.2026"), Some((date(2026, 4, 1), EXPIRATION_KIND_EXPIRATION)));
+    }
+
+    #[test]
+    fn test_no_expiration() {
+        assert_eq!(find_expiration("Invoice 2025-0042\nDate: 2025-01-15\nTotal: 100,00 €"), None);
+        assert_eq!(find_expiration("Valid until further notice."), None);
+    }
+}
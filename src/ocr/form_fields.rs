@@ -0,0 +1,192 @@
+//! Label/value pairing for structured forms.
+//!
+//! Works on OCR text laid out line by line. A field is recognised when a short
+//! label is followed by a colon or a dotted/underscored leader and a value, or
+//! when a label line ending in a colon is followed by its value on the next line.
+//! Lines holding several fields side by side are split on wide gaps first.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeSet;
+
+/// Labels longer than this are prose, not form labels
+const MAX_LABEL_CHARS: usize = 48;
+
+static COLON_FIELD: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^([\p{L}][\p{L}\p{N} /#&().,'-]*?)\s*[:：]\s*(.*)$").unwrap()
+});
+static LEADER_FIELD: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^([\p{L}][\p{L}\p{N} /#&().,'-]*?)\s*(?:\.{3,}|_{3,})\s*(.+)$").unwrap()
+});
+static WIDE_GAP: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s{3,}|\t+").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormField {
+    /// Normalized key, e.g. `date_of_birth`
+    pub key: String,
+    /// Label as printed on the form
+    pub label: String,
+    pub value: String,
+}
+
+/// Extract label/value pairs in reading order. Keys are unique; the first occurrence wins.
+pub fn extract_fields(text: &str) -> Vec<FormField> {
+    let mut fields = Vec::new();
+    let mut seen = BTreeSet::new();
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+
+        let segments: Vec<&str> = WIDE_GAP.split(line).map(str::trim).filter(|s| !s.is_empty()).collect();
+        for segment in &segments {
+            let Some((label, mut value)) = split_field(segment) else {
+                continue;
+            };
+
+            // "Label:" alone on its line takes its value from the next line
+            if value.is_empty() && segments.len() == 1 {
+                if let Some(next) = lines.get(index + 1).filter(|next| !next.is_empty() && split_field(next).is_none()) {
+                    value = next.to_string();
+                }
+            }
+
+            let key = normalize_key(&label);
+            if key.is_empty() || value.is_empty() || !seen.insert(key.clone()) {
+                continue;
+            }
+            fields.push(FormField { key, label, value });
+        }
+    }
+
+    fields
+}
+
+/// Find the value printed after a known label, used for template-guided extraction
+pub fn find_value_for_label(text: &str, label: &str) -> Option<String> {
+    let label_lower = label.to_lowercase();
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+
+    for (index, line) in lines.iter().enumerate() {
+        let lower = line.to_lowercase();
+        let Some(position) = lower.find(&label_lower) else {
+            continue;
+        };
+
+        let rest = line
+            .get(position + label.len()..)
+            .unwrap_or("")
+            .trim_start_matches(|c: char| c == ':' || c == '：' || c == '.' || c == '_' || c.is_whitespace());
+        let value = WIDE_GAP.split(rest).next().unwrap_or("").trim();
+        if !value.is_empty() {
+            return Some(value.to_string());
+        }
+
+        if let Some(next) = lines.get(index + 1).filter(|next| !next.is_empty() && split_field(next).is_none()) {
+            return Some(next.to_string());
+        }
+    }
+
+    None
+}
+
+/// `Date of Birth` -> `date_of_birth`
+pub fn normalize_key(label: &str) -> String {
+    let mut key = String::with_capacity(label.len());
+    for c in label.chars() {
+        if c.is_alphanumeric() {
+            key.extend(c.to_lowercase());
+        } else if !key.ends_with('_') && !key.is_empty() {
+            key.push('_');
+        }
+    }
+    key.trim_end_matches('_').to_string()
+}
+
+/// Similarity of two key sets (Jaccard index)
+pub fn key_overlap(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+fn split_field(segment: &str) -> Option<(String, String)> {
+    let captures = COLON_FIELD
+        .captures(segment)
+        .or_else(|| LEADER_FIELD.captures(segment))?;
+
+    let label = captures.get(1)?.as_str().trim().to_string();
+    let value = captures
+        .get(2)
+        .map(|v| v.as_str().trim().trim_matches('_').trim().to_string())
+        .unwrap_or_default();
+
+    // Prose, times ("10:30") and URLs are not label/value pairs
+    if label.chars().count() > MAX_LABEL_CHARS
+        || label.split_whitespace().count() > 6
+        || (label.ends_with(|c: char| c.is_ascii_digit()) && value.starts_with(|c: char| c.is_ascii_digit()))
+        || value.starts_with("//")
+    {
+        return None;
+    }
+
+    Some((label, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_colon_and_leader_fields() {
+        let text = "APPLICATION FORM\n\
+                    Full Name: Jane Doe\n\
+                    Date of Birth ........ 1990-04-12\n\
+                    Policy No: 12345     Issue Date: 2024-01-01\n";
+
+        let fields = extract_fields(text);
+        let pairs: Vec<(&str, &str)> = fields.iter().map(|f| (f.key.as_str(), f.value.as_str())).collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("full_name", "Jane Doe"),
+                ("date_of_birth", "1990-04-12"),
+                ("policy_no", "12345"),
+                ("issue_date", "2024-01-01"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_value_on_next_line() {
+        let fields = extract_fields("Address:\n12 Main Street\nCity: Springfield");
+        assert_eq!(fields[0].key, "address");
+        assert_eq!(fields[0].value, "12 Main Street");
+        assert_eq!(fields[1].value, "Springfield");
+    }
+
+    #[test]
+    fn test_prose_is_not_a_field() {
+        let text = "Please note that the following terms and conditions apply to every applicant: none";
+        assert!(extract_fields(text).is_empty());
+    }
+
+    #[test]
+    fn test_find_value_for_known_label() {
+        let text = "Employer   ACME Corp\nSalary\n42,000";
+        assert_eq!(find_value_for_label(text, "Employer"), Some("ACME Corp".to_string()));
+        assert_eq!(find_value_for_label(text, "Salary"), Some("42,000".to_string()));
+        assert_eq!(find_value_for_label(text, "Bonus"), None);
+    }
+
+    #[test]
+    fn test_key_overlap() {
+        let a: BTreeSet<String> = ["name", "date", "amount"].iter().map(|s| s.to_string()).collect();
+        let b: BTreeSet<String> = ["name", "date", "signature"].iter().map(|s| s.to_string()).collect();
+        assert!((key_overlap(&a, &b) - 0.5).abs() < f32::EPSILON);
+    }
+}
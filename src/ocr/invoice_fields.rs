@@ -0,0 +1,297 @@
+//! Rule-based invoice extraction.
+//!
+//! Splits lines on wide gaps, so fields printed side by side stay apart, and looks
+//! for segments starting with a known label, such as `Invoice No`, `Total`, `VAT`
+//! or `Due Date` and their German counterparts. The value follows the label, or is
+//! the next segment when the label stands alone. Line items are lines ending in a
+//! quantity, a unit price and an amount that multiply out.
+
+use chrono::NaiveDate;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::models::{InvoiceData, InvoiceLineItem};
+
+const INVOICE_NUMBER_LABELS: &[&str] = &[
+    "invoice number", "invoice no.", "invoice no", "invoice #", "invoice nr", "invoice",
+    "rechnungsnummer", "rechnungs-nr.", "rechnungs-nr", "rechnung nr.", "rechnung nr", "bill number",
+];
+const INVOICE_DATE_LABELS: &[&str] = &[
+    "invoice date", "date of invoice", "rechnungsdatum", "date", "datum",
+];
+const DUE_DATE_LABELS: &[&str] = &[
+    "due date", "payment due", "due", "fälligkeitsdatum", "fällig am", "fällig", "zahlbar bis",
+];
+/// Most specific first, since `total` also starts `total net`
+const TOTAL_LABELS: &[&str] = &[
+    "grand total", "total due", "amount due", "balance due", "invoice total", "total amount", "total",
+    "gesamtbetrag", "rechnungsbetrag", "endbetrag", "bruttobetrag", "summe",
+];
+const TAX_LABELS: &[&str] = &[
+    "vat", "sales tax", "tax", "mwst.", "mwst", "ust.", "ust", "umsatzsteuer", "mehrwertsteuer",
+];
+
+/// Words showing a text is an invoice
+const INVOICE_WORDS: &[&str] = &["invoice", "rechnung", "facture", "factura", "fattura"];
+
+const CURRENCY_SYMBOLS: [(char, &str); 4] = [('€', "EUR"), ('$', "USD"), ('£', "GBP"), ('¥', "JPY")];
+
+static CURRENCY_CODE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(EUR|USD|GBP|CHF|JPY|CAD|AUD|SEK|NOK|DKK|PLN|CZK)\b").unwrap()
+});
+/// A tax rate before the tax amount, as in `VAT 19%` or `MwSt. (19 %)`
+static TAX_RATE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\(?\s*\d+(?:[.,]\d+)?\s*%\s*\)?").unwrap());
+static WIDE_GAP: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s{3,}|\t+").unwrap());
+
+/// Whether the text reads like an invoice rather than, say, a letter mentioning one
+pub fn looks_like_invoice(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    INVOICE_WORDS.iter().any(|word| lower.contains(word)) && find_total(&segments(text)).is_some()
+}
+
+pub fn extract_invoice(text: &str) -> InvoiceData {
+    let segments = segments(text);
+    let total = find_total(&segments);
+    let tax = find_value(&segments, TAX_LABELS, parse_amount);
+
+    let currency = total
+        .as_ref()
+        .and_then(|(_, currency)| currency.clone())
+        .or_else(|| tax.as_ref().and_then(|(_, currency)| currency.clone()))
+        .or_else(|| find_currency(text));
+
+    InvoiceData {
+        vendor: segments
+            .iter()
+            .find(|segment| segment.chars().any(char::is_alphabetic) && !is_invoice_heading(segment))
+            .map(|segment| segment.to_string()),
+        invoice_number: find_value(&segments, INVOICE_NUMBER_LABELS, parse_invoice_number),
+        invoice_date: find_value(&segments, INVOICE_DATE_LABELS, parse_date),
+        due_date: find_value(&segments, DUE_DATE_LABELS, parse_date),
+        total: total.map(|(amount, _)| amount),
+        tax: tax.map(|(amount, _)| amount),
+        currency,
+        line_items: text.lines().filter_map(parse_line_item).collect(),
+    }
+}
+
+/// Non-empty parts of the lines between wide gaps, in reading order
+fn segments(text: &str) -> Vec<&str> {
+    text.lines()
+        .flat_map(|line| WIDE_GAP.split(line))
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// The gross total, from the last segment with a total label since subtotals come first
+fn find_total(segments: &[&str]) -> Option<(f64, Option<String>)> {
+    TOTAL_LABELS.iter().find_map(|label| {
+        (0..segments.len())
+            .rev()
+            .find_map(|index| value_after_label(segments, index, label).and_then(parse_amount))
+    })
+}
+
+/// The first value after one of the labels, trying them in order, that `parse` accepts
+fn find_value<T>(segments: &[&str], labels: &[&str], parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+    labels.iter().find_map(|label| {
+        (0..segments.len()).find_map(|index| value_after_label(segments, index, label).and_then(&parse))
+    })
+}
+
+/// What follows the label when the segment starts with it, or the next segment when
+/// nothing does. A rate before the value, as in `VAT 19%`, is skipped.
+fn value_after_label<'a>(segments: &[&'a str], index: usize, label: &str) -> Option<&'a str> {
+    let segment = segments[index];
+    let head = segment.get(..label.len())?;
+    if head.to_lowercase() != label {
+        return None;
+    }
+    let rest = &segment[label.len()..];
+    if rest.starts_with(char::is_alphanumeric) {
+        return None;
+    }
+
+    let mut value = rest.trim_start_matches(is_label_separator);
+    if let Some(rate) = TAX_RATE.find(value) {
+        value = value[rate.end()..].trim_start_matches(is_label_separator);
+    }
+    if value.is_empty() {
+        segments.get(index + 1).copied()
+    } else {
+        Some(value)
+    }
+}
+
+fn is_label_separator(c: char) -> bool {
+    matches!(c, ':' | '：' | '.' | '#' | '_' | '-') || c.is_whitespace()
+}
+
+fn is_invoice_heading(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    INVOICE_WORDS.iter().any(|word| lower.starts_with(word))
+}
+
+/// An invoice number contains a digit, as opposed to the words after `Invoice` in a title
+fn parse_invoice_number(value: &str) -> Option<String> {
+    let number = value
+        .split_whitespace()
+        .next()?
+        .trim_end_matches([',', ';', ':', ')']);
+    number.chars().any(|c| c.is_ascii_digit()).then(|| number.to_string())
+}
+
+/// An amount such as `1.234,56 €`, `$1,234.56` or `EUR 99`, with the currency it names.
+/// The whole value must be the amount.
+pub fn parse_amount(value: &str) -> Option<(f64, Option<String>)> {
+    let mut currency = None;
+    let mut number = value.trim().to_string();
+    for (symbol, code) in CURRENCY_SYMBOLS {
+        if number.contains(symbol) {
+            currency = Some(code.to_string());
+            number = number.replace(symbol, "");
+        }
+    }
+    if let Some(found) = CURRENCY_CODE.find(&number) {
+        currency = Some(found.as_str().to_string());
+        number = number.replace(found.as_str(), "");
+    }
+
+    let number: String = number.chars().filter(|c| !c.is_whitespace() && *c != '\'').collect();
+    if !number.chars().any(|c| c.is_ascii_digit())
+        || !number.chars().enumerate().all(|(i, c)| c.is_ascii_digit() || c == '.' || c == ',' || (i == 0 && c == '-'))
+    {
+        return None;
+    }
+
+    let amount = normalize_decimal(&number).parse::<f64>().ok().filter(|amount| amount.is_finite())?;
+    Some((amount, currency))
+}
+
+/// Digits with `.` as the decimal separator. With both `.` and `,` the last one
+/// separates decimals; a single `,` before one or two digits does too.
+fn normalize_decimal(number: &str) -> String {
+    let last_dot = number.rfind('.');
+    let last_comma = number.rfind(',');
+    match (last_dot, last_comma) {
+        (Some(dot), Some(comma)) if comma > dot => number.replace('.', "").replace(',', "."),
+        (Some(_), Some(_)) => number.replace(',', ""),
+        (None, Some(comma)) if number.matches(',').count() == 1 && number.len() - comma <= 3 => {
+            number.replace(',', ".")
+        }
+        (None, Some(_)) => number.replace(',', ""),
+        (Some(_), None) if number.matches('.').count() > 1 => number.replace('.', ""),
+        _ => number.to_string(),
+    }
+}
+
+/// A date as printed on invoices: `2024-06-30`, `30.06.2024`, `30/06/2024`,
+/// `06/30/2024`, `June 30, 2024` or `30 June 2024`
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    const FORMATS: [&str; 9] = [
+        "%Y-%m-%d", "%d.%m.%y", "%d.%m.%Y", "%d/%m/%Y", "%m/%d/%Y", "%B %d, %Y", "%b %d, %Y", "%d %B %Y", "%d %b %Y",
+    ];
+    let value = value.trim().trim_end_matches(['.', ',']);
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+}
+
+/// The first currency in the text, by symbol or code
+fn find_currency(text: &str) -> Option<String> {
+    let symbol = text.char_indices().find_map(|(position, c)| {
+        CURRENCY_SYMBOLS
+            .iter()
+            .find(|(symbol, _)| *symbol == c)
+            .map(|(_, code)| (position, code.to_string()))
+    });
+    let code = CURRENCY_CODE.find(text).map(|found| (found.start(), found.as_str().to_string()));
+    match (symbol, code) {
+        (Some(symbol), Some(code)) => Some(if symbol.0 < code.0 { symbol.1 } else { code.1 }),
+        (symbol, code) => symbol.or(code).map(|(_, currency)| currency),
+    }
+}
+
+/// `Consulting  2  50,00 €  100,00 €`: a description, then quantity, unit price
+/// and amount, where the quantity times the unit price is the amount
+fn parse_line_item(line: &str) -> Option<InvoiceLineItem> {
+    let mut cleaned = line.to_string();
+    for (symbol, _) in CURRENCY_SYMBOLS {
+        cleaned = cleaned.replace(symbol, " ");
+    }
+    let cleaned = CURRENCY_CODE.replace_all(&cleaned, " ");
+    let tokens: Vec<&str> = cleaned.split_whitespace().collect();
+    if tokens.len() < 4 {
+        return None;
+    }
+
+    let (description, numbers) = tokens.split_at(tokens.len() - 3);
+    let amount = |token: &str| parse_amount(token).map(|(amount, _)| amount);
+    let quantity = amount(numbers[0].trim_end_matches(['x', 'X']))?;
+    let unit_price = amount(numbers[1])?;
+    let total = amount(numbers[2])?;
+    if quantity <= 0.0 || (quantity * unit_price - total).abs() > 0.01 * total.abs().max(1.0) {
+        return None;
+    }
+
+    let description = description.join(" ");
+    if !description.chars().any(char::is_alphabetic) {
+        return None;
+    }
+    Some(InvoiceLineItem {
+        description,
+        quantity: Some(quantity),
+        unit_price: Some(unit_price),
+        amount: Some(total),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVOICE: &str = "ACME Corp\n\
+                           12 Industrial Way\n\
+                           INVOICE\n\
+                           Invoice No: INV-2024-113     Date: 15.05.2024\n\
+                           Due Date: 2024-06-14\n\
+                           Consulting hours   10   85,00 €   850,00 €\n\
+                           Travel expenses   1   120,00 €   120,00 €\n\
+                           Subtotal   970,00 €\n\
+                           VAT 19%   184,30 €\n\
+                           Total   1.154,30 €\n";
+
+    #[test]
+    fn test_extracts_invoice_fields() {
+        let invoice = extract_invoice(INVOICE);
+        assert_eq!(invoice.vendor.as_deref(), Some("ACME Corp"));
+        assert_eq!(invoice.invoice_number.as_deref(), Some("INV-2024-113"));
+        assert_eq!(invoice.invoice_date, NaiveDate::from_ymd_opt(2024, 5, 15));
+        assert_eq!(invoice.due_date, NaiveDate::from_ymd_opt(2024, 6, 14));
+        assert_eq!(invoice.total, Some(1154.3));
+        assert_eq!(invoice.tax, Some(184.3));
+        assert_eq!(invoice.currency.as_deref(), Some("EUR"));
+        assert_eq!(invoice.line_items.len(), 2);
+        assert_eq!(invoice.line_items[0].description, "Consulting hours");
+        assert_eq!(invoice.line_items[0].amount, Some(850.0));
+        assert!(looks_like_invoice(INVOICE));
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("1.234,56 €"), Some((1234.56, Some("EUR".to_string()))));
+        assert_eq!(parse_amount("$1,234.56"), Some((1234.56, Some("USD".to_string()))));
+        assert_eq!(parse_amount("CHF 99"), Some((99.0, Some("CHF".to_string()))));
+        assert_eq!(parse_amount("12,5"), Some((12.5, None)));
+        assert_eq!(parse_amount("1,234"), Some((1234.0, None)));
+        assert_eq!(parse_amount("net 100.00"), None);
+    }
+
+    #[test]
+    fn test_letter_is_not_an_invoice() {
+        let letter = "Dear customer,\nthank you for paying our invoice last month.\nKind regards";
+        assert!(!looks_like_invoice(letter));
+        assert!(extract_invoice(letter).is_empty());
+    }
+}
@@ -1,10 +1,23 @@
 pub mod api;
+pub mod email_parser;
 pub mod enhanced;
 pub mod enhanced_processing;
 pub mod error;
+pub mod expiration_dates;
+pub mod form_fields;
 pub mod health;
+pub mod invoice_fields;
+pub mod page_artifacts;
+pub mod page_checkpoints;
+pub mod page_transforms;
+#[cfg(feature = "ocr")]
+pub mod page_images;
+pub mod pdf_pages;
+pub mod perceptual_hash;
+pub mod pii;
 pub mod queue;
 pub mod tests;
+pub mod text_normalization;
 pub mod xml_extractor;
 
 use anyhow::{anyhow, Result};
@@ -14,6 +27,15 @@ use crate::ocr::health::OcrHealthChecker;
 
 #[cfg(feature = "ocr")]
 use tesseract::Tesseract;
+#[cfg(feature = "ocr")]
+use crate::services::converter_sandbox::{hash_file, SandboxedCommand};
+
+/// Wall clock limit of one ocrmypdf or pdftotext run in the converter sandbox
+#[cfg(feature = "ocr")]
+pub(crate) const OCRMYPDF_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// pdftotext and ocrmypdf end every page of extracted text with a form feed
+pub const PAGE_BREAK: char = '\u{c}';
 
 pub struct OcrService {
     health_checker: OcrHealthChecker,
@@ -105,9 +127,19 @@ impl OcrService {
             let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
             let temp_text_path = format!("{}/pdf_text_{}.txt", temp_dir, std::process::id());
             
+            // Every tool runs in the converter sandbox
+            let file_hash = hash_file(Path::new(file_path)).await.ok();
+            let sandboxed = |program: &str| {
+                let command = SandboxedCommand::new(program).timeout(OCRMYPDF_TIMEOUT);
+                match &file_hash {
+                    Some(file_hash) => command.input_hash(file_hash.clone()),
+                    None => command,
+                }
+            };
+
             // Progressive extraction with fallback strategies
             // Strategy 1: pdftotext for existing text (fastest)
-            let mut output = tokio::process::Command::new("pdftotext")
+            let mut output = sandboxed("pdftotext")
                 .arg("-layout")  // Preserve layout
                 .arg(file_path)
                 .arg(&temp_text_path)
@@ -127,7 +159,7 @@ impl OcrService {
             
             if !output.status.success() {
                 // Strategy 2: ocrmypdf sidecar (when pdftotext fails)
-                output = tokio::process::Command::new("ocrmypdf")
+                output = sandboxed("ocrmypdf")
                     .arg("--sidecar")    // Extract text to sidecar file
                     .arg(&temp_text_path)
                     .arg(file_path)
@@ -137,7 +169,7 @@ impl OcrService {
                     
                 if !output.status.success() {
                     // Final fallback: minimal processing (may skip large pages)
-                    output = tokio::process::Command::new("ocrmypdf")
+                    output = sandboxed("ocrmypdf")
                         .arg("--skip-big")   // Skip very large pages to avoid memory issues
                         .arg("--sidecar")
                         .arg(&temp_text_path)
@@ -0,0 +1,309 @@
+//! Heuristic detection of stamps, signatures and embedded figures on page images.
+//!
+//! Pages are split into a grid of square cells. Each cell records how much of it
+//! is saturated colored ink (typical of rubber stamps) and how much is dark ink.
+//! Neighbouring cells are grouped into regions which are then classified by their
+//! ink density, size and position. Text-heavy regions are weeded out later by
+//! running OCR on each candidate crop.
+
+use std::collections::VecDeque;
+
+use crate::models::{ArtifactKind, BoundingBox};
+
+/// Edge length of a grid cell in pixels
+pub const CELL_SIZE: u32 = 16;
+
+/// Fraction of colored pixels for a cell to count as stamp ink
+const STAMP_CELL_RATIO: f32 = 0.08;
+/// Fraction of dark pixels for a cell to count as containing ink at all
+const INK_CELL_RATIO: f32 = 0.02;
+/// Minimum region size (in cells) for stamps
+const MIN_STAMP_CELLS: usize = 9;
+/// Figures must cover at least this fraction of the page
+const MIN_FIGURE_PAGE_FRACTION: f32 = 0.04;
+/// Average ink density above which a region looks like a figure/photo
+const FIGURE_DENSITY: f32 = 0.30;
+/// Average ink density below which a wide region looks like handwriting strokes
+const SIGNATURE_MAX_DENSITY: f32 = 0.12;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CellStats {
+    /// Fraction of pixels that are saturated, non-dark color
+    pub colored_ratio: f32,
+    /// Fraction of pixels that are dark (black/grey ink)
+    pub ink_ratio: f32,
+}
+
+/// Cell statistics for a whole page, row-major
+#[derive(Debug, Clone)]
+pub struct CellGrid {
+    pub columns: usize,
+    pub rows: usize,
+    pub cells: Vec<CellStats>,
+    pub page_width: u32,
+    pub page_height: u32,
+}
+
+impl CellGrid {
+    fn get(&self, column: usize, row: usize) -> CellStats {
+        self.cells[row * self.columns + column]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactCandidate {
+    pub kind: ArtifactKind,
+    pub bbox: BoundingBox,
+    pub score: f32,
+}
+
+/// Build the cell grid for an RGB page image
+#[cfg(feature = "ocr")]
+pub fn compute_cell_grid(image: &image::RgbImage) -> CellGrid {
+    let (width, height) = image.dimensions();
+    let columns = width.div_ceil(CELL_SIZE) as usize;
+    let rows = height.div_ceil(CELL_SIZE) as usize;
+    let mut cells = Vec::with_capacity(columns * rows);
+
+    for row in 0..rows as u32 {
+        for column in 0..columns as u32 {
+            let x0 = column * CELL_SIZE;
+            let y0 = row * CELL_SIZE;
+            let x1 = (x0 + CELL_SIZE).min(width);
+            let y1 = (y0 + CELL_SIZE).min(height);
+
+            let mut colored = 0u32;
+            let mut dark = 0u32;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let [r, g, b] = image.get_pixel(x, y).0;
+                    let max = r.max(g).max(b);
+                    let min = r.min(g).min(b);
+                    if max - min > 80 && max > 60 {
+                        colored += 1;
+                    } else if max < 110 {
+                        dark += 1;
+                    }
+                }
+            }
+
+            let total = ((x1 - x0) * (y1 - y0)).max(1) as f32;
+            cells.push(CellStats {
+                colored_ratio: colored as f32 / total,
+                ink_ratio: dark as f32 / total,
+            });
+        }
+    }
+
+    CellGrid { columns, rows, cells, page_width: width, page_height: height }
+}
+
+struct Region {
+    min_column: usize,
+    min_row: usize,
+    max_column: usize,
+    max_row: usize,
+    cell_count: usize,
+    density_sum: f32,
+}
+
+impl Region {
+    fn bbox(&self, grid: &CellGrid) -> BoundingBox {
+        let x = self.min_column as u32 * CELL_SIZE;
+        let y = self.min_row as u32 * CELL_SIZE;
+        let right = ((self.max_column as u32 + 1) * CELL_SIZE).min(grid.page_width);
+        let bottom = ((self.max_row as u32 + 1) * CELL_SIZE).min(grid.page_height);
+        BoundingBox { x, y, width: right - x, height: bottom - y }
+    }
+
+    fn columns(&self) -> usize {
+        self.max_column - self.min_column + 1
+    }
+
+    fn rows(&self) -> usize {
+        self.max_row - self.min_row + 1
+    }
+
+    fn mean_density(&self) -> f32 {
+        self.density_sum / self.cell_count.max(1) as f32
+    }
+}
+
+/// Group cells accepted by `is_member` into 4-connected regions
+fn connected_regions<F>(grid: &CellGrid, is_member: F, density: fn(CellStats) -> f32) -> Vec<Region>
+where
+    F: Fn(CellStats) -> bool,
+{
+    let mut visited = vec![false; grid.cells.len()];
+    let mut regions = Vec::new();
+
+    for start in 0..grid.cells.len() {
+        if visited[start] || !is_member(grid.cells[start]) {
+            continue;
+        }
+
+        let mut region = Region {
+            min_column: usize::MAX,
+            min_row: usize::MAX,
+            max_column: 0,
+            max_row: 0,
+            cell_count: 0,
+            density_sum: 0.0,
+        };
+        let mut queue = VecDeque::from([start]);
+        visited[start] = true;
+
+        while let Some(index) = queue.pop_front() {
+            let column = index % grid.columns;
+            let row = index / grid.columns;
+            let stats = grid.get(column, row);
+
+            region.min_column = region.min_column.min(column);
+            region.min_row = region.min_row.min(row);
+            region.max_column = region.max_column.max(column);
+            region.max_row = region.max_row.max(row);
+            region.cell_count += 1;
+            region.density_sum += density(stats);
+
+            let mut neighbours = Vec::with_capacity(4);
+            if column > 0 { neighbours.push(index - 1); }
+            if column + 1 < grid.columns { neighbours.push(index + 1); }
+            if row > 0 { neighbours.push(index - grid.columns); }
+            if row + 1 < grid.rows { neighbours.push(index + grid.columns); }
+
+            for neighbour in neighbours {
+                if !visited[neighbour] && is_member(grid.cells[neighbour]) {
+                    visited[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        regions.push(region);
+    }
+
+    regions
+}
+
+/// Classify regions of the grid into stamp, signature and figure candidates
+pub fn detect_candidates(grid: &CellGrid) -> Vec<ArtifactCandidate> {
+    let mut candidates = Vec::new();
+    if grid.cells.is_empty() {
+        return candidates;
+    }
+
+    for region in connected_regions(grid, |c| c.colored_ratio >= STAMP_CELL_RATIO, |c| c.colored_ratio) {
+        if region.cell_count >= MIN_STAMP_CELLS && region.columns() >= 3 && region.rows() >= 3 {
+            candidates.push(ArtifactCandidate {
+                kind: ArtifactKind::Stamp,
+                bbox: region.bbox(grid),
+                score: region.mean_density().min(1.0),
+            });
+        }
+    }
+
+    let total_cells = grid.cells.len() as f32;
+    for region in connected_regions(
+        grid,
+        |c| c.ink_ratio >= INK_CELL_RATIO && c.colored_ratio < STAMP_CELL_RATIO,
+        |c| c.ink_ratio,
+    ) {
+        let density = region.mean_density();
+        let bbox_cells = (region.columns() * region.rows()) as f32;
+
+        if bbox_cells / total_cells >= MIN_FIGURE_PAGE_FRACTION && density >= FIGURE_DENSITY {
+            candidates.push(ArtifactCandidate {
+                kind: ArtifactKind::Figure,
+                bbox: region.bbox(grid),
+                score: density.min(1.0),
+            });
+            continue;
+        }
+
+        let center_row = (region.min_row + region.max_row) as f32 / 2.0;
+        let in_lower_half = center_row >= grid.rows as f32 / 2.0;
+        let wide = region.columns() >= 6 && region.columns() >= region.rows() * 2;
+        if in_lower_half && wide && region.rows() <= 8 && density < SIGNATURE_MAX_DENSITY {
+            candidates.push(ArtifactCandidate {
+                kind: ArtifactKind::Signature,
+                bbox: region.bbox(grid),
+                score: (1.0 - density / SIGNATURE_MAX_DENSITY).clamp(0.0, 1.0),
+            });
+        }
+    }
+
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_with(columns: usize, rows: usize, fill: impl Fn(usize, usize) -> CellStats) -> CellGrid {
+        let mut cells = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                cells.push(fill(column, row));
+            }
+        }
+        CellGrid {
+            columns,
+            rows,
+            cells,
+            page_width: columns as u32 * CELL_SIZE,
+            page_height: rows as u32 * CELL_SIZE,
+        }
+    }
+
+    #[test]
+    fn test_detects_colored_stamp() {
+        let grid = grid_with(40, 50, |c, r| {
+            if (30..35).contains(&c) && (5..10).contains(&r) {
+                CellStats { colored_ratio: 0.4, ink_ratio: 0.0 }
+            } else {
+                CellStats::default()
+            }
+        });
+
+        let candidates = detect_candidates(&grid);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].kind, ArtifactKind::Stamp);
+        assert_eq!(candidates[0].bbox, BoundingBox { x: 480, y: 80, width: 80, height: 80 });
+    }
+
+    #[test]
+    fn test_detects_dense_figure() {
+        let grid = grid_with(40, 50, |c, r| {
+            if (5..25).contains(&c) && (10..25).contains(&r) {
+                CellStats { colored_ratio: 0.0, ink_ratio: 0.6 }
+            } else {
+                CellStats::default()
+            }
+        });
+
+        let candidates = detect_candidates(&grid);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].kind, ArtifactKind::Figure);
+    }
+
+    #[test]
+    fn test_detects_sparse_signature_in_lower_half() {
+        let grid = grid_with(40, 50, |c, r| {
+            if (20..32).contains(&c) && (40..43).contains(&r) {
+                CellStats { colored_ratio: 0.0, ink_ratio: 0.05 }
+            } else {
+                CellStats::default()
+            }
+        });
+
+        let candidates = detect_candidates(&grid);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].kind, ArtifactKind::Signature);
+    }
+
+    #[test]
+    fn test_blank_page_has_no_candidates() {
+        let grid = grid_with(40, 50, |_, _| CellStats::default());
+        assert!(detect_candidates(&grid).is_empty());
+    }
+}
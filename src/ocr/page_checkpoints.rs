@@ -0,0 +1,66 @@
+//! Pages OCRed so far for the document an OCR queue job works on. PDFs are OCRed page
+//! by page under a job, each page saved as it is done, so a job stopped by a shutdown
+//! continues from the first page missing instead of starting over.
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::db::Database;
+
+/// Scanned PDFs with fewer pages are OCRed as a whole, as they are quick to redo
+const DEFAULT_MIN_PAGES: usize = 10;
+
+tokio::task_local! {
+    static CURRENT: PageCheckpoints;
+}
+
+#[derive(Clone)]
+pub struct PageCheckpoints {
+    db: Database,
+    document_id: Uuid,
+}
+
+impl PageCheckpoints {
+    pub fn new(db: Database, document_id: Uuid) -> Self {
+        Self { db, document_id }
+    }
+
+    /// Run the OCR of the document with its checkpoints
+    pub async fn scope<F: Future>(self, ocr: F) -> F::Output {
+        CURRENT.scope(self, ocr).await
+    }
+
+    /// Checkpoints of the document being OCRed, when under a queue job
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Text and confidence of the pages already done, by page number
+    pub async fn load(&self) -> HashMap<i32, (String, f32)> {
+        match self.db.get_ocr_page_checkpoints(self.document_id).await {
+            Ok(pages) => pages,
+            Err(e) => {
+                warn!("Failed to load OCR checkpoints of document {}: {}", self.document_id, e);
+                HashMap::new()
+            }
+        }
+    }
+
+    pub async fn save(&self, page_number: i32, text: &str, confidence: f32) {
+        if let Err(e) = self.db.save_ocr_page_checkpoint(self.document_id, page_number, text, confidence).await {
+            warn!("Failed to save OCR checkpoint of document {} page {}: {}", self.document_id, page_number, e);
+        }
+    }
+}
+
+/// Fewest pages of a scanned PDF OCRed page by page under a job, from
+/// `OCR_CHECKPOINT_MIN_PAGES`
+pub fn min_pages() -> usize {
+    std::env::var("OCR_CHECKPOINT_MIN_PAGES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MIN_PAGES)
+}
@@ -0,0 +1,181 @@
+//! Rasterize documents into per-page images for page-level analysis.
+//! Only available with the `ocr` feature.
+
+use anyhow::{anyhow, Result};
+use tracing::warn;
+use uuid::Uuid;
+
+use image::DynamicImage;
+
+use crate::services::converter_sandbox::SandboxedCommand;
+
+/// Render up to `max_pages` pages of a PDF or image document at `dpi`.
+/// Images are returned as a single page.
+pub async fn render_pages(data: &[u8], mime_type: &str, dpi: u32, max_pages: usize) -> Result<Vec<DynamicImage>> {
+    render_page_range(data, mime_type, dpi, 1, max_pages.max(1) as u32).await
+}
+
+/// Render pages `first..=last` (1-based) of a PDF or image document at `dpi`.
+/// Images are returned as a single page.
+pub async fn render_page_range(data: &[u8], mime_type: &str, dpi: u32, first: u32, last: u32) -> Result<Vec<DynamicImage>> {
+    if mime_type.starts_with("image/") {
+        let img = image::load_from_memory(data)?;
+        return Ok(vec![img]);
+    }
+
+    if mime_type != "application/pdf" {
+        return Err(anyhow!("Page rendering is not supported for {}", mime_type));
+    }
+
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let prefix = format!("{}/pages_{}", temp_dir, Uuid::new_v4());
+    let pdf_path = format!("{}.pdf", prefix);
+    tokio::fs::write(&pdf_path, data).await?;
+
+    let output = SandboxedCommand::new("pdftoppm")
+        .arg("-r").arg(dpi.to_string())
+        .arg("-f").arg(first.max(1).to_string())
+        .arg("-l").arg(last.max(first).to_string())
+        .arg("-png")
+        .arg(&pdf_path)
+        .arg(&prefix)
+        .input(data)
+        .output()
+        .await;
+
+    let _ = tokio::fs::remove_file(&pdf_path).await;
+
+    let output = output?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "pdftoppm failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    // pdftoppm names pages <prefix>-<n>.png with n zero-padded to the page count width
+    let parent = std::path::Path::new(&prefix).parent().map(|p| p.to_path_buf()).unwrap_or_default();
+    let stem = std::path::Path::new(&prefix)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut page_files = Vec::new();
+    let mut entries = tokio::fs::read_dir(&parent).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some(rest) = name.strip_prefix(&format!("{}-", stem)) {
+            if let Some(number) = rest.strip_suffix(".png").and_then(|n| n.parse::<usize>().ok()) {
+                page_files.push((number, entry.path()));
+            }
+        }
+    }
+    page_files.sort_by_key(|(number, _)| *number);
+
+    let mut pages = Vec::with_capacity(page_files.len());
+    for (number, path) in page_files {
+        match tokio::fs::read(&path).await {
+            Ok(png) => match image::load_from_memory(&png) {
+                Ok(img) => pages.push(img),
+                Err(e) => warn!("Failed to decode rendered page {}: {}", number, e),
+            },
+            Err(e) => warn!("Failed to read rendered page {}: {}", number, e),
+        }
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+
+    Ok(pages)
+}
+
+/// Encode an image as PNG bytes
+pub fn encode_png(img: &DynamicImage) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut buffer), image::ImageFormat::Png)?;
+    Ok(buffer)
+}
+
+/// Run Tesseract on a single in-memory image
+pub async fn ocr_image(img: &DynamicImage, lang: &str) -> Result<String> {
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let path = format!("{}/region_{}.png", temp_dir, Uuid::new_v4());
+    img.save_with_format(&path, image::ImageFormat::Png)?;
+
+    let lang = lang.to_string();
+    let ocr_path = path.clone();
+    let result = tokio::task::spawn_blocking(move || -> Result<String> {
+        let mut tesseract = tesseract::Tesseract::new(None, Some(&lang))
+            .map_err(|e| anyhow!("Failed to initialize Tesseract: {}", e))?
+            .set_image(&ocr_path)
+            .map_err(|e| anyhow!("Failed to load region image: {}", e))?;
+        let text = tesseract.get_text()
+            .map_err(|e| anyhow!("Failed to extract text: {}", e))?;
+        Ok(text.trim().to_string())
+    })
+    .await
+    .map_err(|e| anyhow!("OCR task panicked: {}", e))?;
+
+    let _ = tokio::fs::remove_file(&path).await;
+    result
+}
+
+/// Run Tesseract on a single in-memory image and return its TSV output, which has
+/// the pixel box of every recognized word
+pub async fn ocr_image_tsv(img: &DynamicImage, lang: &str) -> Result<String> {
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let path = format!("{}/words_{}.png", temp_dir, Uuid::new_v4());
+    img.save_with_format(&path, image::ImageFormat::Png)?;
+
+    let lang = lang.to_string();
+    let ocr_path = path.clone();
+    let result = tokio::task::spawn_blocking(move || -> Result<String> {
+        let mut tesseract = tesseract::Tesseract::new(None, Some(&lang))
+            .map_err(|e| anyhow!("Failed to initialize Tesseract: {}", e))?
+            .set_image(&ocr_path)
+            .map_err(|e| anyhow!("Failed to load page image: {}", e))?;
+        tesseract.get_tsv_text(0)
+            .map_err(|e| anyhow!("Failed to extract words: {}", e))
+    })
+    .await
+    .map_err(|e| anyhow!("OCR task panicked: {}", e))?;
+
+    let _ = tokio::fs::remove_file(&path).await;
+    result
+}
+
+/// Assemble page images, in order, into a PDF with an OCR text layer of what the
+/// images show
+pub async fn images_to_pdf(pages: &[std::path::PathBuf], lang: &str) -> Result<Vec<u8>> {
+    if pages.is_empty() {
+        return Err(anyhow!("No pages to assemble"));
+    }
+
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let base = format!("{}/assembled_{}", temp_dir, Uuid::new_v4());
+    let list_path = format!("{}.txt", base);
+    let list: String = pages.iter().map(|page| format!("{}\n", page.display())).collect();
+    tokio::fs::write(&list_path, list).await?;
+
+    // Tesseract writes <base>.pdf
+    let output = SandboxedCommand::new("tesseract")
+        .arg(&list_path)
+        .arg(&base)
+        .arg("-l").arg(lang)
+        .arg("pdf")
+        .output()
+        .await;
+    let _ = tokio::fs::remove_file(&list_path).await;
+
+    let output = output?;
+    let pdf_path = format!("{}.pdf", base);
+    if !output.status.success() {
+        let _ = tokio::fs::remove_file(&pdf_path).await;
+        return Err(anyhow!(
+            "tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let pdf = tokio::fs::read(&pdf_path).await;
+    let _ = tokio::fs::remove_file(&pdf_path).await;
+    Ok(pdf?)
+}
@@ -0,0 +1,233 @@
+//! Turning scanned pages upright. Each page's rotation and deskew are detected once,
+//! before OCR, and recorded, so OCR, page images, thumbnails and searchable PDFs all
+//! apply the same transform, and a page rotated by hand stays as it was set.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::models::PageTransform;
+
+/// Smallest skew worth correcting, in degrees
+pub const MIN_SKEW_DEGREES: f32 = 0.3;
+
+/// Lowest Tesseract orientation confidence a detected rotation is applied at
+#[cfg(feature = "ocr")]
+const MIN_ORIENTATION_CONFIDENCE: f32 = 2.0;
+
+/// Longest side pages are scaled down to before estimating their skew
+#[cfg(feature = "ocr")]
+const SKEW_SAMPLE_SIZE: u32 = 800;
+
+tokio::task_local! {
+    static CURRENT: PageTransforms;
+}
+
+/// Transforms of the pages of the document being OCRed, by page number
+#[derive(Clone, Default)]
+pub struct PageTransforms {
+    pages: Arc<HashMap<i32, PageTransform>>,
+}
+
+impl PageTransforms {
+    pub fn new(transforms: Vec<PageTransform>) -> Self {
+        Self {
+            pages: Arc::new(transforms.into_iter().map(|t| (t.page_number, t)).collect()),
+        }
+    }
+
+    /// Run the OCR of the document with its page transforms
+    pub async fn scope<F: Future>(self, ocr: F) -> F::Output {
+        CURRENT.scope(self, ocr).await
+    }
+
+    /// Page transforms of the document being OCRed, when under a queue job
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Transform of a page, when it is not left as it is
+    pub fn get(&self, page_number: i32) -> Option<&PageTransform> {
+        self.pages.get(&page_number).filter(|t| !t.is_identity())
+    }
+
+    /// Whether any of the pages is turned or deskewed
+    pub fn any(&self) -> bool {
+        self.pages.values().any(|t| !t.is_identity())
+    }
+}
+
+/// Rotation and deskew detected for a page
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detected {
+    /// Clockwise, in degrees
+    pub rotation: i32,
+    pub skew_degrees: f32,
+    pub confidence: Option<f32>,
+}
+
+/// The clockwise rotation that puts the text upright and its confidence, from the
+/// output of `tesseract <image> stdout --psm 0`
+pub fn parse_osd(output: &str) -> Option<(i32, f32)> {
+    let field = |name: &str| {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(|value| value.trim_start_matches(':').trim())
+    };
+    let rotation = field("Rotate")?.parse::<i32>().ok()?;
+    let confidence = field("Orientation confidence")?.parse::<f32>().ok()?;
+    crate::models::normalize_rotation(rotation).map(|rotation| (rotation, confidence))
+}
+
+/// The clockwise tilt of text lines, in degrees, from the dark pixels of an upright
+/// page: the angle at which the pixels' row profile is most sharply peaked
+pub fn estimate_skew(points: &[(f32, f32)], max_degrees: f32, step: f32) -> f32 {
+    if points.is_empty() || step <= 0.0 {
+        return 0.0;
+    }
+
+    let score = |degrees: f32| {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut rows: HashMap<i64, u64> = HashMap::new();
+        for &(x, y) in points {
+            *rows.entry((y * cos - x * sin).round() as i64).or_default() += 1;
+        }
+        rows.values().map(|&count| count * count).sum::<u64>()
+    };
+
+    let steps = (max_degrees / step).round() as i32;
+    let mut best = (0.0, score(0.0));
+    for i in -steps..=steps {
+        let degrees = i as f32 * step;
+        let value = score(degrees);
+        if value > best.1 {
+            best = (degrees, value);
+        }
+    }
+    best.0
+}
+
+/// Detect how a page image is turned: its rotation from Tesseract's orientation
+/// detection, then its skew once rotated upright
+#[cfg(feature = "ocr")]
+pub async fn detect(img: &image::DynamicImage) -> anyhow::Result<Detected> {
+    use crate::models::MAX_SKEW_DEGREES;
+
+    let (rotation, confidence) = match detect_orientation(img).await {
+        Ok(Some((rotation, confidence))) if confidence >= MIN_ORIENTATION_CONFIDENCE => (rotation, Some(confidence)),
+        Ok(detected) => (0, detected.map(|(_, confidence)| confidence)),
+        Err(e) => {
+            tracing::debug!("Orientation detection failed, keeping the page as it is: {}", e);
+            (0, None)
+        }
+    };
+
+    let sample = apply(&img.thumbnail(SKEW_SAMPLE_SIZE, SKEW_SAMPLE_SIZE), rotation, 0.0).to_luma8();
+    let points: Vec<(f32, f32)> = tokio::task::spawn_blocking(move || {
+        sample
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel.0[0] < 128)
+            .map(|(x, y, _)| (x as f32, y as f32))
+            .collect()
+    })
+    .await?;
+    let skew = estimate_skew(&points, MAX_SKEW_DEGREES, 0.2);
+
+    Ok(Detected {
+        rotation,
+        skew_degrees: if skew.abs() < MIN_SKEW_DEGREES { 0.0 } else { skew },
+        confidence,
+    })
+}
+
+#[cfg(feature = "ocr")]
+async fn detect_orientation(img: &image::DynamicImage) -> anyhow::Result<Option<(i32, f32)>> {
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let path = format!("{}/osd_{}.png", temp_dir, uuid::Uuid::new_v4());
+    img.save_with_format(&path, image::ImageFormat::Png)?;
+
+    let output = crate::services::converter_sandbox::SandboxedCommand::new("tesseract")
+        .arg(&path)
+        .arg("stdout")
+        .arg("--psm")
+        .arg("0")
+        .output()
+        .await;
+    let _ = tokio::fs::remove_file(&path).await;
+
+    // Tesseract fails OSD on pages with too little text, which are left as they are
+    let output = output?;
+    Ok(parse_osd(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Turn a page image upright: rotate it clockwise by `rotation`, then deskew it
+/// counter-clockwise by `skew_degrees`, filling the uncovered corners with white
+#[cfg(feature = "ocr")]
+pub fn apply(img: &image::DynamicImage, rotation: i32, skew_degrees: f32) -> image::DynamicImage {
+    use imageproc::geometric_transformations::{rotate_about_center, Interpolation};
+
+    let rotated = match rotation {
+        90 => img.rotate90(),
+        180 => img.rotate180(),
+        270 => img.rotate270(),
+        _ => img.clone(),
+    };
+    if skew_degrees == 0.0 {
+        return rotated;
+    }
+
+    let deskewed = rotate_about_center(
+        &rotated.to_rgb8(),
+        -skew_degrees.to_radians(),
+        Interpolation::Bilinear,
+        image::Rgb([255, 255, 255]),
+    );
+    image::DynamicImage::ImageRgb8(deskewed)
+}
+
+/// [`apply`] with a page's recorded transform
+#[cfg(feature = "ocr")]
+pub fn apply_transform(img: &image::DynamicImage, transform: &PageTransform) -> image::DynamicImage {
+    apply(img, transform.rotation, transform.skew_degrees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_parse_osd() {
+        let output = "Page number: 0\nOrientation in degrees: 270\nRotate: 90\nOrientation confidence: 4.27\nScript: Latin\nScript confidence: 1.85\n";
+        assert_eq!(parse_osd(output), Some((90, 4.27)));
+        assert_eq!(parse_osd("Too few characters. Skipping this page\n"), None);
+    }
+
+    #[test]
+    fn test_estimate_skew() {
+        // Three text lines tilted 2 degrees clockwise
+        let slope = 2f32.to_radians().tan();
+        let points: Vec<(f32, f32)> = (0..3)
+            .flat_map(|line| (0..400).map(move |x| (x as f32, 50.0 + line as f32 * 40.0 + x as f32 * slope)))
+            .collect();
+
+        let skew = estimate_skew(&points, 10.0, 0.2);
+        assert!((skew - 2.0).abs() <= 0.2, "estimated {}", skew);
+
+        let level: Vec<(f32, f32)> = points.iter().map(|&(x, y)| (x, y - x * slope)).collect();
+        assert_eq!(estimate_skew(&level, 10.0, 0.2), 0.0);
+    }
+
+    #[test]
+    fn test_transforms_skip_identity() {
+        let document_id = Uuid::new_v4();
+        let turned = PageTransform { rotation: 180, ..PageTransform::identity(document_id, 2) };
+        let transforms = PageTransforms::new(vec![PageTransform::identity(document_id, 1), turned.clone()]);
+
+        assert!(transforms.get(1).is_none());
+        assert_eq!(transforms.get(2), Some(&turned));
+        assert!(transforms.any());
+        assert!(!PageTransforms::default().any());
+    }
+}
@@ -0,0 +1,193 @@
+//! Page-level PDF operations (page count, per-page text, page extraction and
+//! reassembly) backed by the poppler command line tools.
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use super::PAGE_BREAK;
+use crate::services::converter_sandbox::SandboxedCommand;
+
+/// A PDF written to the temp directory for the duration of a page operation.
+/// The file is removed when the value is dropped.
+pub struct TempPdf {
+    path: PathBuf,
+}
+
+impl TempPdf {
+    pub async fn from_bytes(data: &[u8]) -> Result<Self> {
+        let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+        let path = PathBuf::from(temp_dir).join(format!("pdfpages_{}.pdf", Uuid::new_v4()));
+        tokio::fs::write(&path, data).await?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of pages reported by `pdfinfo`
+    pub async fn page_count(&self) -> Result<u32> {
+        page_count(&self.path).await
+    }
+
+    /// Embedded text of a single page (1-based). Scanned pages without a text layer return an empty string.
+    pub async fn page_text(&self, page: u32) -> Result<String> {
+        let page = page.to_string();
+        let output = run(
+            "pdftotext",
+            &["-layout".as_ref(), "-f".as_ref(), page.as_ref(), "-l".as_ref(), page.as_ref(), self.path.as_os_str(), "-".as_ref()],
+        )
+        .await?;
+        Ok(String::from_utf8_lossy(&output).trim().to_string())
+    }
+
+    /// Build a new PDF from the given 1-based pages, in the given order
+    pub async fn extract_pages(&self, pages: &[u32]) -> Result<Vec<u8>> {
+        if pages.is_empty() {
+            return Err(anyhow!("No pages selected"));
+        }
+
+        let work_dir = self.path.with_extension("pages");
+        tokio::fs::create_dir_all(&work_dir).await?;
+        let result = self.extract_pages_into(&work_dir, pages).await;
+        let _ = tokio::fs::remove_dir_all(&work_dir).await;
+        result
+    }
+
+    async fn extract_pages_into(&self, work_dir: &Path, pages: &[u32]) -> Result<Vec<u8>> {
+        let mut page_files = Vec::with_capacity(pages.len());
+        for (index, page) in pages.iter().enumerate() {
+            let page_file = work_dir.join(format!("{}.pdf", index));
+            let page = page.to_string();
+            run(
+                "pdfseparate",
+                &["-f".as_ref(), page.as_ref(), "-l".as_ref(), page.as_ref(), self.path.as_os_str(), page_file.as_os_str()],
+            )
+            .await?;
+            page_files.push(page_file);
+        }
+
+        let combined = work_dir.join("combined.pdf");
+        if page_files.len() == 1 {
+            tokio::fs::rename(&page_files[0], &combined).await?;
+        } else {
+            let mut args: Vec<&std::ffi::OsStr> = page_files.iter().map(|p| p.as_os_str()).collect();
+            args.push(combined.as_os_str());
+            run("pdfunite", &args).await?;
+        }
+
+        Ok(tokio::fs::read(&combined).await?)
+    }
+}
+
+impl Drop for TempPdf {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Concatenate whole PDFs in order
+pub async fn concatenate(documents: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let mut parts = Vec::with_capacity(documents.len());
+    for data in documents {
+        parts.push(TempPdf::from_bytes(data).await?);
+    }
+
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let output = PathBuf::from(temp_dir).join(format!("pdfunite_{}.pdf", Uuid::new_v4()));
+    let mut args: Vec<&std::ffi::OsStr> = parts.iter().map(|p| p.path().as_os_str()).collect();
+    args.push(output.as_os_str());
+
+    let result = match run("pdfunite", &args).await {
+        Ok(_) => tokio::fs::read(&output).await.map_err(Into::into),
+        Err(e) => Err(e),
+    };
+    let _ = tokio::fs::remove_file(&output).await;
+    result
+}
+
+/// Embedded text of every page of the PDF at `path`, in page order, from a single
+/// `pdftotext` run. Scanned pages without a text layer are empty strings.
+pub async fn page_texts(path: &Path) -> Result<Vec<String>> {
+    let output = run("pdftotext", &["-layout".as_ref(), path.as_os_str(), "-".as_ref()]).await?;
+    Ok(split_pages(&String::from_utf8_lossy(&output)))
+}
+
+/// 1-based numbers of the pages whose text layer has fewer than `min_words` words,
+/// e.g. scans that at most carry a printed page number
+pub fn pages_without_text(pages: &[String], min_words: usize) -> Vec<u32> {
+    pages
+        .iter()
+        .enumerate()
+        .filter(|(_, text)| {
+            text.split_whitespace()
+                .filter(|word| word.chars().any(char::is_alphanumeric))
+                .count()
+                < min_words
+        })
+        .map(|(index, _)| index as u32 + 1)
+        .collect()
+}
+
+/// Number of pages `pdfinfo` reports for a PDF already on disk
+pub async fn page_count(path: &Path) -> Result<u32> {
+    let output = run("pdfinfo", &[path.as_os_str()]).await?;
+    parse_page_count(&String::from_utf8_lossy(&output))
+        .ok_or_else(|| anyhow!("pdfinfo did not report a page count"))
+}
+
+/// Pages of `pdftotext` output, which ends every page with a form feed
+fn split_pages(output: &str) -> Vec<String> {
+    let mut pages: Vec<String> = output.split(PAGE_BREAK).map(|page| page.trim().to_string()).collect();
+    if output.ends_with(PAGE_BREAK) {
+        pages.pop();
+    }
+    pages
+}
+
+/// Run a poppler tool in the converter sandbox
+async fn run(program: &str, args: &[&std::ffi::OsStr]) -> Result<Vec<u8>> {
+    let output = SandboxedCommand::new(program).args(args).output().await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+fn parse_page_count(pdfinfo_output: &str) -> Option<u32> {
+    pdfinfo_output
+        .lines()
+        .find_map(|line| line.strip_prefix("Pages:"))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_page_count() {
+        let output = "Title:          scan\nProducer:       ocrmypdf\nPages:          12\nEncrypted:      no\n";
+        assert_eq!(parse_page_count(output), Some(12));
+        assert_eq!(parse_page_count("Title: x\n"), None);
+    }
+
+    #[test]
+    fn test_split_pages() {
+        assert_eq!(split_pages("Invoice\n\x0c\x0c  Terms \n\x0c"), vec!["Invoice", "", "Terms"]);
+        assert_eq!(split_pages("single page"), vec!["single page"]);
+    }
+
+    #[test]
+    fn test_pages_without_text() {
+        let pages = vec!["Dear customer, your invoice".to_string(), "- 2 -".to_string(), String::new()];
+        assert_eq!(pages_without_text(&pages, 3), vec![2, 3]);
+    }
+}
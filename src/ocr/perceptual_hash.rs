@@ -0,0 +1,193 @@
+//! Perceptual hashes of page images for finding re-scans of the same paper document.
+//!
+//! A page is cropped to the bounding box of its ink, so a sheet placed slightly
+//! differently on the scanner still hashes alike, then averaged down to a 9x8 grid.
+//! Each bit of the 64-bit hash records whether a cell is clearly lighter than its
+//! left neighbour (a difference hash), which survives rescaling, compression noise
+//! and brightness changes but not different content.
+
+/// Pixels darker than this count as ink when finding the content area
+const INK_THRESHOLD: u8 = 200;
+const HASH_COLUMNS: u32 = 9;
+const HASH_ROWS: u32 = 8;
+/// Neighbouring cells closer than this in average gray level count as equal, so
+/// scanner noise on blank paper does not flip bits
+const FLAT_TOLERANCE: f32 = 4.0;
+/// Grid pages are compared on, about a line of text per row on an A4 page
+const DIFFERENCE_COLUMNS: u32 = 48;
+const DIFFERENCE_ROWS: u32 = 64;
+/// Cells further apart than this in average gray level count as changed
+const DIFFERENCE_TOLERANCE: f32 = 24.0;
+
+/// Grayscale page image, row-major
+#[derive(Debug, Clone, Copy)]
+pub struct GrayPage<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: &'a [u8],
+}
+
+impl GrayPage<'_> {
+    fn pixel(&self, x: u32, y: u32) -> u8 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+/// Bounding box `(left, top, right, bottom)` of the page's ink, right and bottom
+/// exclusive; None for a blank page
+pub fn content_bounds(page: &GrayPage) -> Option<(u32, u32, u32, u32)> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for y in 0..page.height {
+        for x in 0..page.width {
+            if page.pixel(x, y) >= INK_THRESHOLD {
+                continue;
+            }
+            bounds = Some(match bounds {
+                None => (x, y, x + 1, y + 1),
+                Some((left, top, right, bottom)) => (left.min(x), top.min(y), right.max(x + 1), bottom.max(y + 1)),
+            });
+        }
+    }
+    bounds
+}
+
+/// Average gray level of each cell of a `columns` x `rows` grid laid over the
+/// page's content area, row-major
+fn cell_averages(page: &GrayPage, columns: u32, rows: u32) -> Vec<f32> {
+    let (left, top, right, bottom) = content_bounds(page).unwrap_or((0, 0, page.width, page.height));
+    let (width, height) = (right - left, bottom - top);
+
+    let mut cells = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        let y0 = top + height * row / rows;
+        let y1 = (top + height * (row + 1) / rows).max(y0 + 1);
+        for column in 0..columns {
+            let x0 = left + width * column / columns;
+            let x1 = (left + width * (column + 1) / columns).max(x0 + 1);
+
+            let mut sum = 0u64;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    sum += page.pixel(x, y) as u64;
+                }
+            }
+            cells.push(sum as f32 / ((x1 - x0) * (y1 - y0)) as f32);
+        }
+    }
+    cells
+}
+
+pub fn difference_hash(page: &GrayPage) -> u64 {
+    if page.width == 0 || page.height == 0 {
+        return 0;
+    }
+    let cells = cell_averages(page, HASH_COLUMNS, HASH_ROWS);
+
+    let mut hash = 0u64;
+    for row in cells.chunks(HASH_COLUMNS as usize) {
+        for pair in row.windows(2) {
+            hash = (hash << 1) | u64::from(pair[1] - pair[0] > FLAT_TOLERANCE);
+        }
+    }
+    hash
+}
+
+/// Share of a fine grid over the two pages' content areas whose cells differ
+/// clearly in gray level: 0.0 for pages that look the same, towards 1.0 for pages
+/// with nothing in common. Unlike the hash it notices a changed word or figure.
+pub fn page_difference(a: &GrayPage, b: &GrayPage) -> f64 {
+    match (a.width == 0 || a.height == 0, b.width == 0 || b.height == 0) {
+        (true, true) => return 0.0,
+        (true, false) | (false, true) => return 1.0,
+        (false, false) => {}
+    }
+    let a = cell_averages(a, DIFFERENCE_COLUMNS, DIFFERENCE_ROWS);
+    let b = cell_averages(b, DIFFERENCE_COLUMNS, DIFFERENCE_ROWS);
+    let differing = a.iter().zip(&b).filter(|(a, b)| (*a - *b).abs() > DIFFERENCE_TOLERANCE).count();
+    differing as f64 / a.len() as f64
+}
+
+#[cfg(feature = "ocr")]
+pub fn page_hash(image: &image::DynamicImage) -> u64 {
+    let gray = image.to_luma8();
+    difference_hash(&GrayPage { width: gray.width(), height: gray.height(), pixels: gray.as_raw() })
+}
+
+#[cfg(feature = "ocr")]
+pub fn image_difference(a: &image::DynamicImage, b: &image::DynamicImage) -> f64 {
+    let (a, b) = (a.to_luma8(), b.to_luma8());
+    page_difference(
+        &GrayPage { width: a.width(), height: a.height(), pixels: a.as_raw() },
+        &GrayPage { width: b.width(), height: b.height(), pixels: b.as_raw() },
+    )
+}
+
+/// Number of differing bits between two hashes
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A white page with dark bars whose lengths vary by line, shifted by `offset`
+    fn page(offset: u32, ink: impl Fn(u32) -> u32) -> Vec<u8> {
+        let (width, height) = (120, 160);
+        let mut pixels = vec![250u8; (width * height) as usize];
+        for line in 0..10 {
+            let y = 20 + offset + line * 12;
+            for x in (15 + offset)..(15 + offset + ink(line)) {
+                for dy in 0..6 {
+                    pixels[((y + dy) * width + x) as usize] = 30;
+                }
+            }
+        }
+        pixels
+    }
+
+    fn hash(pixels: &[u8]) -> u64 {
+        difference_hash(&GrayPage { width: 120, height: 160, pixels })
+    }
+
+    #[test]
+    fn test_rescan_hashes_alike() {
+        let original = page(0, |line| 40 + line * 5);
+        let mut rescan = page(6, |line| 40 + line * 5);
+        // Slightly different exposure and a little noise
+        for (i, pixel) in rescan.iter_mut().enumerate() {
+            *pixel = pixel.saturating_sub(8).saturating_add((i % 7) as u8);
+        }
+
+        assert!(hamming_distance(hash(&original), hash(&rescan)) <= 4);
+    }
+
+    #[test]
+    fn test_different_pages_hash_apart() {
+        let first = page(0, |line| 40 + line * 5);
+        let second = page(0, |line| if line % 2 == 0 { 90 } else { 20 });
+
+        assert!(hamming_distance(hash(&first), hash(&second)) > 10);
+        assert_eq!(content_bounds(&GrayPage { width: 2, height: 2, pixels: &[255; 4] }), None);
+    }
+
+    #[test]
+    fn test_page_difference() {
+        let original = page(0, |line| 40 + line * 5);
+        let difference = |pixels: &[u8]| {
+            page_difference(&GrayPage { width: 120, height: 160, pixels: &original }, &GrayPage { width: 120, height: 160, pixels })
+        };
+
+        assert_eq!(difference(&original), 0.0);
+        assert_eq!(difference(&page(6, |line| 40 + line * 5)), 0.0);
+
+        // One line longer, as when a word was added
+        let revised = difference(&page(0, |line| if line == 4 { 70 } else { 40 + line * 5 }));
+        assert!(revised > 0.0 && revised < 0.05);
+        assert!(difference(&page(0, |line| if line % 2 == 0 { 90 } else { 20 })) > revised);
+
+        let blank = GrayPage { width: 0, height: 0, pixels: &[] };
+        assert_eq!(page_difference(&blank, &GrayPage { width: 120, height: 160, pixels: &original }), 1.0);
+        assert_eq!(page_difference(&blank, &blank), 0.0);
+    }
+}
@@ -0,0 +1,158 @@
+//! Pattern-based detection of personal data in document text.
+//!
+//! Candidates are found with regular expressions and then checked where the format
+//! allows it: IBANs must pass the mod-97 check, card numbers the Luhn check, and
+//! social security numbers must not use area, group or serial numbers that are never
+//! issued. Phone numbers need an international prefix or enough digits not to be
+//! dates or amounts. When candidates overlap, the more specific kind wins.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::models::PiiKind;
+
+static IBAN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]){11,30}\b").unwrap());
+static CREDIT_CARD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap());
+static SSN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(\d{3})[- ](\d{2})[- ](\d{4})\b").unwrap());
+static EMAIL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9](?:[a-z0-9-]*[a-z0-9])?(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b").unwrap());
+static PHONE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,5}\)[ .-]?)?\d{2,5}(?:[ ./-]\d{2,8}){1,4}").unwrap());
+
+/// Digits a phone number without an international prefix needs, so that dates and
+/// amounts are not taken for one
+const MIN_LOCAL_PHONE_DIGITS: usize = 9;
+
+/// Personal data found at a byte range of the text
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiiMatch {
+    pub kind: PiiKind,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Every piece of personal data the patterns find, in text order
+pub fn detect(text: &str) -> Vec<PiiMatch> {
+    let mut matches: Vec<PiiMatch> = Vec::new();
+    let mut add = |kind: PiiKind, start: usize, end: usize| {
+        if matches.iter().any(|m| start < m.end && m.start < end) {
+            return;
+        }
+        matches.push(PiiMatch { kind, start, end, text: text[start..end].to_string() });
+    };
+
+    // Most specific first, since an IBAN or card number also looks like a phone number
+    for m in IBAN.find_iter(text) {
+        // The pattern may run on into a following code, so drop trailing groups until it checks out
+        let mut candidate = m.as_str();
+        while !is_valid_iban(candidate) {
+            match candidate.rfind(' ') {
+                Some(space) => candidate = &candidate[..space],
+                None => break,
+            }
+        }
+        if is_valid_iban(candidate) {
+            add(PiiKind::Iban, m.start(), m.start() + candidate.len());
+        }
+    }
+    for m in CREDIT_CARD.find_iter(text) {
+        if passes_luhn(m.as_str()) {
+            add(PiiKind::CreditCard, m.start(), m.end());
+        }
+    }
+    for caps in SSN.captures_iter(text) {
+        let number = |i: usize| caps[i].parse::<u32>().unwrap_or(0);
+        let (area, group, serial) = (number(1), number(2), number(3));
+        if area != 0 && area != 666 && area < 900 && group != 0 && serial != 0 {
+            let m = caps.get(0).expect("whole match");
+            add(PiiKind::Ssn, m.start(), m.end());
+        }
+    }
+    for m in EMAIL.find_iter(text) {
+        add(PiiKind::Email, m.start(), m.end());
+    }
+    for m in PHONE.find_iter(text) {
+        let digits = m.as_str().chars().filter(char::is_ascii_digit).count();
+        let international = m.as_str().starts_with('+');
+        if (7..=15).contains(&digits) && (international || digits >= MIN_LOCAL_PHONE_DIGITS) {
+            add(PiiKind::Phone, m.start(), m.end());
+        }
+    }
+
+    matches.sort_by_key(|m| m.start);
+    matches
+}
+
+/// ISO 13616 check: moving the first four characters to the end and reading letters
+/// as 10-35 gives a number that leaves 1 when divided by 97
+pub fn is_valid_iban(candidate: &str) -> bool {
+    let iban: String = candidate.chars().filter(|c| !c.is_whitespace()).collect();
+    if !(15..=34).contains(&iban.len()) || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+
+    let rearranged = iban[4..].chars().chain(iban[..4].chars());
+    let mut remainder: u32 = 0;
+    for c in rearranged {
+        let value = match c.to_digit(36) {
+            Some(value) => value,
+            None => return false,
+        };
+        remainder = if value < 10 { (remainder * 10 + value) % 97 } else { (remainder * 100 + value) % 97 };
+    }
+    remainder == 1
+}
+
+/// Luhn checksum of a 13 to 19 digit card number, ignoring spaces and dashes
+pub fn passes_luhn(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) || digits.iter().all(|d| *d == digits[0]) {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    sum % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert!(is_valid_iban("DE89 3704 0044 0532 0130 00"));
+        assert!(is_valid_iban("GB82WEST12345698765432"));
+        assert!(!is_valid_iban("DE89 3704 0044 0532 0130 01"));
+        assert!(passes_luhn("4111 1111 1111 1111"));
+        assert!(passes_luhn("5500-0000-0000-0004"));
+        assert!(!passes_luhn("4111 1111 1111 1112"));
+        assert!(!passes_luhn("0000 0000 0000 0000"));
+    }
+
+    #[test]
+    fn test_detect() {
+        let text = "Contact jane.doe@example.com or +49 30 1234567.\n\
+                    SSN 123-45-6789, card 4111 1111 1111 1111.\n\
+                    IBAN DE89 3704 0044 0532 0130 00, invoice dated 2024-01-15, total 1.234,56";
+        let found: Vec<(PiiKind, &str)> = detect(text).iter().map(|m| (m.kind, &text[m.start..m.end])).collect();
+        assert_eq!(
+            found,
+            vec![
+                (PiiKind::Email, "jane.doe@example.com"),
+                (PiiKind::Phone, "+49 30 1234567"),
+                (PiiKind::Ssn, "123-45-6789"),
+                (PiiKind::CreditCard, "4111 1111 1111 1111"),
+                (PiiKind::Iban, "DE89 3704 0044 0532 0130 00"),
+            ]
+        );
+
+        // Never issued, so not a social security number
+        assert!(detect("Ref 666-12-3456").is_empty());
+    }
+}
@@ -3,12 +3,21 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool, Row, Column};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
 use tokio::sync::Semaphore;
 use tokio::time::{sleep, Duration};
-use tracing::{error, info, warn};
+use tracing::{error, info, warn, Instrument};
 use uuid::Uuid;
 
+use crate::errors::panic::catch_panic;
+use crate::ocr::error::OcrError;
+use crate::ocr::page_checkpoints::PageCheckpoints;
+use crate::ocr::text_normalization;
+use crate::scheduling::shutdown;
+use crate::models::QueueKind;
+use crate::services::{document_progress, maintenance_service, processing_usage};
+use crate::services::page_transform_service::PageTransformService;
+use crate::services::vision_fallback_service::{VisionFallbackService, VISION_TRANSCRIPTION_CONFIDENCE};
 use crate::{db::Database, ocr::enhanced::EnhancedOcrService, db_guardrails_simple::DocumentTransactionManager, monitoring::request_throttler::RequestThrottler};
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -38,15 +47,42 @@ pub struct QueueStats {
     pub oldest_pending_minutes: Option<f64>,
 }
 
+/// Upper bound for concurrent OCR jobs per server, protects the DB connection pool
+pub const MAX_CONCURRENT_OCR_JOBS: usize = 15;
+
+/// Processing statistics of this server's OCR worker since startup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerThroughput {
+    pub worker_id: String,
+    pub max_concurrent_jobs: usize,
+    pub active_jobs: usize,
+    pub completed: u64,
+    pub failed: u64,
+    pub avg_processing_time_ms: Option<f64>,
+}
+
+#[derive(Default)]
+struct ThroughputCounters {
+    completed: AtomicU64,
+    failed: AtomicU64,
+    total_processing_ms: AtomicU64,
+}
+
 #[derive(Clone)]
 pub struct OcrQueueService {
     db: Database,
     pool: PgPool,
-    max_concurrent_jobs: usize,
+    max_concurrent_jobs: Arc<AtomicUsize>,
+    job_slots: Arc<Semaphore>,
+    /// Job slots left over from lowering the limit, retired as their jobs finish
+    retiring_slots: Arc<std::sync::Mutex<usize>>,
+    throughput: Arc<ThroughputCounters>,
     worker_id: String,
     transaction_manager: DocumentTransactionManager,
     processing_throttler: Arc<RequestThrottler>,
     is_paused: Arc<AtomicBool>,
+    /// Unix time of the worker loop's latest iteration, 0 until it starts
+    heartbeat: Arc<AtomicI64>,
     file_service: std::sync::Arc<crate::services::file_service::FileService>,
 }
 
@@ -57,8 +93,9 @@ impl OcrQueueService {
         
         // Create a processing throttler to limit concurrent OCR operations
         // This prevents overwhelming the database connection pool
+        let max_concurrent_jobs = max_concurrent_jobs.clamp(1, MAX_CONCURRENT_OCR_JOBS);
         let processing_throttler = Arc::new(RequestThrottler::new(
-            max_concurrent_jobs.min(15), // Don't exceed 15 concurrent OCR processes
+            MAX_CONCURRENT_OCR_JOBS, // Don't exceed 15 concurrent OCR processes
             60, // 60 second max wait time for OCR processing
             format!("ocr-processing-{}", worker_id),
         ));
@@ -66,11 +103,15 @@ impl OcrQueueService {
         Self {
             db,
             pool,
-            max_concurrent_jobs,
+            max_concurrent_jobs: Arc::new(AtomicUsize::new(max_concurrent_jobs)),
+            job_slots: Arc::new(Semaphore::new(max_concurrent_jobs)),
+            retiring_slots: Arc::new(std::sync::Mutex::new(0)),
+            throughput: Arc::new(ThroughputCounters::default()),
             worker_id,
             transaction_manager,
             processing_throttler,
             is_paused: Arc::new(AtomicBool::new(false)),
+            heartbeat: Arc::new(AtomicI64::new(0)),
             file_service,
         }
     }
@@ -303,6 +344,9 @@ impl OcrQueueService {
         .execute(&self.pool)
         .await?;
 
+        self.throughput.completed.fetch_add(1, Ordering::Relaxed);
+        self.throughput.total_processing_ms.fetch_add(processing_time_ms.max(0) as u64, Ordering::Relaxed);
+
         Ok(())
     }
 
@@ -327,6 +371,8 @@ impl OcrQueueService {
         .fetch_one(&self.pool)
         .await?;
 
+        self.throughput.failed.fetch_add(1, Ordering::Relaxed);
+
         let status: Option<String> = result.get("status");
         if status == Some("failed".to_string()) {
             error!("OCR job {} permanently failed after max attempts: {}", item_id, error);
@@ -335,6 +381,57 @@ impl OcrQueueService {
         Ok(())
     }
 
+    /// Hand an item back to the queue without counting the attempt, for a job stopped
+    /// by a shutdown
+    async fn release_item(&self, item_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE ocr_queue
+            SET status = 'pending',
+                started_at = NULL,
+                worker_id = NULL,
+                attempts = GREATEST(attempts - 1, 0)
+            WHERE id = $1 AND status = 'processing'
+            "#
+        )
+        .bind(item_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Hand the items this worker still works on back to the queue, when it stops before
+    /// they finish
+    pub async fn release_claimed_items(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE ocr_queue
+            SET status = 'pending',
+                started_at = NULL,
+                worker_id = NULL,
+                attempts = GREATEST(attempts - 1, 0)
+            WHERE worker_id = $1 AND status = 'processing'
+            "#
+        )
+        .bind(&self.worker_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Pages of the file an OCR run goes through: those of a PDF, or one for an image
+    async fn ocr_page_count(path: &std::path::Path, mime_type: &str) -> i32 {
+        if mime_type == "application/pdf" {
+            crate::ocr::pdf_pages::page_count(path).await.map_or(0, |count| count as i32)
+        } else if mime_type.starts_with("image/") {
+            1
+        } else {
+            0
+        }
+    }
+
     /// Process a single queue item
     pub async fn process_item(&self, item: OcrQueueItem, ocr_service: &EnhancedOcrService) -> Result<()> {
         let start_time = std::time::Instant::now();
@@ -367,16 +464,72 @@ impl OcrQueueService {
                     item.id, item.document_id, filename, mime_type, file_size_mb
                 );
                 // Get user's OCR settings or use defaults
-                let settings = if let Some(user_id) = user_id {
+                let mut settings = if let Some(user_id) = user_id {
                     self.db.get_user_settings(user_id).await.ok().flatten()
                         .unwrap_or_else(|| crate::models::Settings::default())
                 } else {
                     crate::models::Settings::default()
                 };
+                // Documents in a workspace are processed with the workspace's settings
+                match self.db.get_document_workspace_settings(item.document_id).await {
+                    Ok(Some(workspace_settings)) => workspace_settings.apply_to(&mut settings),
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to load workspace settings for document {}: {}", item.document_id, e),
+                }
+
+                document_progress::ocr_started(user_id, item.document_id);
+
+                // Perform enhanced OCR; encrypted files are read from a temporary decrypted copy
+                let plaintext = self.file_service.plaintext_path(&file_path).await;
+                let extraction = match &plaintext {
+                    Ok(plaintext) => {
+                        // Pages are turned upright as detected or set by hand before they are OCRed
+                        let transforms = PageTransformService::new(self.db.clone(), (*self.file_service).clone())
+                            .prepare(item.document_id, &file_path, plaintext.path(), &mime_type, settings.ocr_detect_orientation)
+                            .await;
+                        let extraction = PageCheckpoints::new(self.db.clone(), item.document_id)
+                            .scope(ocr_service.extract_text_with_context(plaintext.path(), &mime_type, &filename, file_size, &settings));
+                        transforms.scope(extraction).await
+                    }
+                    Err(e) => Err(anyhow::anyhow!("Failed to decrypt file: {}", e)),
+                };
+                if matches!(&extraction, Err(e) if matches!(e.downcast_ref::<OcrError>(), Some(OcrError::Interrupted))) {
+                    self.release_item(item.id).await?;
+                    info!("OCR job {} for '{}' stopped for shutdown, keeping the pages done so far", item.id, filename);
+                    return Ok(());
+                }
+                // The job ends here either way, so the pages it saved are no longer needed
+                if let Err(e) = self.db.clear_ocr_page_checkpoints(item.document_id).await {
+                    warn!("Failed to clear OCR checkpoints of document {}: {}", item.document_id, e);
+                }
+                let pages = match &plaintext {
+                    Ok(plaintext) => Self::ocr_page_count(std::path::Path::new(plaintext.path()), &mime_type).await,
+                    Err(_) => 0,
+                };
+                if let Err(e) = self.db.record_ocr_usage(item.document_id, start_time.elapsed().as_secs_f64(), pages).await {
+                    warn!("Failed to record OCR usage of document {}: {}", item.document_id, e);
+                }
+                match extraction {
+                    Ok(mut ocr_result) => {
+                        // Very poor scans get a second chance through the vision model, within the user's quota
+                        if let Some(user_id) = user_id {
+                            let vision_fallback = VisionFallbackService::new(self.db.clone(), (*self.file_service).clone());
+                            if vision_fallback.should_attempt(&mime_type, ocr_result.confidence) {
+                                match vision_fallback.transcribe(item.document_id, user_id, &file_path, &mime_type, ocr_result.confidence).await {
+                                    Ok(Some(transcription)) if !transcription.text.is_empty() => {
+                                        info!("Using vision transcription by {} for '{}' ({} pages)",
+                                              transcription.model, filename, transcription.pages.len());
+                                        ocr_result.word_count = ocr_service.count_words_safely(&transcription.text);
+                                        ocr_result.text = transcription.text;
+                                        ocr_result.confidence = VISION_TRANSCRIPTION_CONFIDENCE;
+                                        ocr_result.preprocessing_applied.push("vision_llm_fallback".to_string());
+                                    }
+                                    Ok(_) => {}
+                                    Err(e) => warn!("Vision fallback failed for document {}: {}", item.document_id, e),
+                                }
+                            }
+                        }
 
-                // Perform enhanced OCR
-                match ocr_service.extract_text_with_context(&file_path, &mime_type, &filename, file_size, &settings).await {
-                    Ok(ocr_result) => {
                         // Validate OCR quality
                         if let Err(validation_error) = ocr_service.validate_ocr_quality(&ocr_result, &settings) {
                             let error_msg = format!("OCR quality validation failed: {}", validation_error);
@@ -410,10 +563,23 @@ impl OcrQueueService {
                             .execute(&self.pool)
                             .await?;
                             
+                            document_progress::ocr_failed(user_id, item.document_id);
+                            
                             self.mark_failed(item.id, &error_msg).await?;
                             return Ok(());
                         }
                         
+                        // The normalized text is stored and indexed; the raw text is kept beside it
+                        let mut raw_text = None;
+                        if text_normalization::enabled() {
+                            let normalized = text_normalization::normalize_ocr_text(&ocr_result.text);
+                            if normalized != ocr_result.text {
+                                ocr_result.word_count = ocr_service.count_words_safely(&normalized);
+                                raw_text = Some(std::mem::replace(&mut ocr_result.text, normalized));
+                                ocr_result.preprocessing_applied.push("text_normalization".to_string());
+                            }
+                        }
+
                         if !ocr_result.text.is_empty() {
                             // Use transaction-safe OCR update to prevent corruption
                             let processing_time_ms = start_time.elapsed().as_millis() as i64;
@@ -428,6 +594,11 @@ impl OcrQueueService {
                             ).await {
                                 Ok(true) => {
                                     info!("✅ Transaction-safe OCR update successful for document {}", item.document_id);
+                                    if let Some(raw_text) = &raw_text {
+                                        if let Err(e) = self.db.set_document_ocr_text_raw(item.document_id, &ocr_result.text, raw_text).await {
+                                            warn!("Failed to keep the raw OCR text of document {}: {}", item.document_id, e);
+                                        }
+                                    }
                                 }
                                 Ok(false) => {
                                     let error_msg = "OCR update failed validation (document may have been modified)";
@@ -447,6 +618,8 @@ impl OcrQueueService {
                                         None,
                                     ).await;
                                     
+                                    document_progress::ocr_failed(user_id, item.document_id);
+                                    
                                     self.mark_failed(item.id, error_msg).await?;
                                     return Ok(());
                                 }
@@ -468,6 +641,8 @@ impl OcrQueueService {
                                         None,
                                     ).await;
                                     
+                                    document_progress::ocr_failed(user_id, item.document_id);
+                                    
                                     self.mark_failed(item.id, &error_msg).await?;
                                     return Ok(());
                                 }
@@ -508,6 +683,8 @@ impl OcrQueueService {
                             .execute(&self.pool)
                             .await?;
                             
+                            document_progress::ocr_failed(user_id, item.document_id);
+                            
                             self.mark_failed(item.id, &error_msg).await?;
                             return Ok(());
                         }
@@ -540,6 +717,76 @@ impl OcrQueueService {
                         let processing_time_ms = start_time.elapsed().as_millis() as i32;
                         self.mark_completed(item.id, processing_time_ms).await?;
                         
+                        crate::services::event_service::EventService::new(self.db.clone())
+                            .publish_best_effort(
+                                crate::models::EventType::OcrCompleted,
+                                user_id,
+                                Some(item.document_id),
+                                serde_json::json!({
+                                    "document_id": item.document_id,
+                                    "confidence": ocr_result.confidence,
+                                    "word_count": ocr_result.word_count,
+                                    "processing_time_ms": processing_time_ms,
+                                }),
+                            )
+                            .await;
+                        
+                        // Analysis ends once every step started below has finished
+                        let _analysis = document_progress::begin_analysis(user_id, item.document_id);
+                        if crate::services::page_artifact_service::PageArtifactService::enabled_after_ocr()
+                            && (mime_type.starts_with("image/") || mime_type == "application/pdf")
+                        {
+                            self.spawn_page_artifact_extraction(item.document_id);
+                        }
+
+                        if crate::services::ocr_word_service::OcrWordService::enabled_after_ocr()
+                            && (mime_type.starts_with("image/") || mime_type == "application/pdf")
+                        {
+                            self.spawn_word_confidence_analysis(item.document_id, settings.ocr_language.clone());
+                        }
+
+                        if crate::services::similar_document_service::SimilarDocumentService::enabled_after_ocr()
+                            && (mime_type.starts_with("image/") || mime_type == "application/pdf")
+                        {
+                            self.spawn_page_hashing(item.document_id);
+                        }
+
+                        if crate::services::form_extraction_service::FormExtractionService::enabled_after_ocr() {
+                            self.spawn_form_extraction(item.document_id);
+                        }
+
+                        if crate::services::omr_service::OmrService::enabled_after_ocr()
+                            && (mime_type.starts_with("image/") || mime_type == "application/pdf")
+                        {
+                            self.spawn_omr_extraction(item.document_id, settings.ocr_language.clone());
+                        }
+
+                        if crate::ocr::email_parser::is_email(&mime_type, &filename) {
+                            self.spawn_email_attachment_extraction(item.document_id);
+                        }
+
+                        self.spawn_saved_search_matching(item.document_id);
+                        self.spawn_correspondent_assignment(item.document_id);
+                        self.spawn_label_rules(item.document_id);
+
+                        if crate::services::invoice_extraction_service::InvoiceExtractionService::enabled_after_ocr() {
+                            self.spawn_invoice_extraction(item.document_id);
+                        }
+                        if crate::services::expiration_service::ExpirationService::enabled_after_ocr() {
+                            self.spawn_expiration_extraction(item.document_id);
+                        }
+                        if crate::services::title_service::TitleService::enabled_after_ocr() {
+                            self.spawn_title_generation(item.document_id);
+                        }
+                        if crate::services::pii_service::PiiService::enabled_after_ocr() {
+                            self.spawn_pii_scan(item.document_id);
+                        }
+                        // The contents may have been replaced, which can break signatures
+                        if crate::services::pdf_signature_service::enabled() && mime_type == "application/pdf" {
+                            self.spawn_signature_check(item.document_id);
+                        }
+                        self.spawn_post_consume_hooks(item.document_id);
+                        
                         info!(
                             "✅ OCR completed for '{}' | Job: {} | Document: {} | {:.1}% confidence | {} words | {}ms | Preprocessing: {:?}",
                             filename, item.id, item.document_id, 
@@ -594,6 +841,20 @@ impl OcrQueueService {
                         .await?;
                         
                         self.mark_failed(item.id, &error_msg).await?;
+                        
+                        document_progress::ocr_failed(user_id, item.document_id);
+                        crate::services::event_service::EventService::new(self.db.clone())
+                            .publish_best_effort(
+                                crate::models::EventType::OcrFailed,
+                                user_id,
+                                Some(item.document_id),
+                                serde_json::json!({
+                                    "document_id": item.document_id,
+                                    "failure_reason": failure_reason,
+                                    "error": error_msg,
+                                }),
+                            )
+                            .await;
                     }
                 }
             }
@@ -606,6 +867,297 @@ impl OcrQueueService {
         Ok(())
     }
 
+    /// Detect page artifacts for a freshly OCR'd document in the background
+    fn spawn_page_artifact_extraction(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        let file_service = (*self.file_service).clone();
+        document_progress::spawn_analysis_step(document_id, format!("Page artifact extraction for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for page artifact extraction: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::page_artifact_service::PageArtifactService::new(db, file_service);
+            if let Err(e) = service.extract_for_document(&document).await {
+                warn!("Page artifact extraction failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Record the confidence of every word of a freshly OCR'd document in the background
+    fn spawn_word_confidence_analysis(&self, document_id: Uuid, language: String) {
+        let db = self.db.clone();
+        let file_service = (*self.file_service).clone();
+        document_progress::spawn_analysis_step(document_id, format!("Word confidence analysis for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for word confidence analysis: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::ocr_word_service::OcrWordService::new(db, file_service);
+            if let Err(e) = service.analyze_document(&document, &language).await {
+                warn!("Word confidence analysis failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Hash the pages of a freshly OCR'd document and flag it if it looks like a re-scan
+    fn spawn_page_hashing(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        let file_service = (*self.file_service).clone();
+        document_progress::spawn_analysis_step(document_id, format!("Page hashing for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for page hashing: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::similar_document_service::SimilarDocumentService::new(db, file_service);
+            if let Err(e) = service.hash_and_flag(&document).await {
+                warn!("Page hashing failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Extract form fields for a freshly OCR'd document in the background
+    fn spawn_form_extraction(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        document_progress::spawn_analysis_step(document_id, format!("Form extraction for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for form extraction: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::form_extraction_service::FormExtractionService::new(db);
+            if let Err(e) = service.extract_for_document(&document).await {
+                warn!("Form extraction failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Read the marks and fields of a freshly OCR'd scan matching a form template in the background
+    fn spawn_omr_extraction(&self, document_id: Uuid, language: String) {
+        let db = self.db.clone();
+        let file_service = (*self.file_service).clone();
+        document_progress::spawn_analysis_step(document_id, format!("Mark recognition for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for mark recognition: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::omr_service::OmrService::new(db, file_service);
+            if let Err(e) = service.process_document(&document, &language).await {
+                warn!("Mark recognition failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Ingest the attachments of a freshly processed email in the background
+    fn spawn_email_attachment_extraction(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        let file_service = (*self.file_service).clone();
+        document_progress::spawn_analysis_step(document_id, format!("Attachment extraction for email {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load email {} for attachment extraction: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::email_attachment_service::EmailAttachmentService::new(db, file_service);
+            if let Err(e) = service.extract_attachments(&document).await {
+                warn!("Attachment extraction failed for email {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Notify users whose saved searches match a freshly processed document
+    fn spawn_saved_search_matching(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        document_progress::spawn_analysis_step(document_id, format!("Saved search matching for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for saved search matching: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::saved_search_service::SavedSearchService::new(db);
+            if let Err(e) = service.notify_matches(&document).await {
+                warn!("Saved search matching failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Give a freshly processed document a correspondent by rules, name or LLM
+    fn spawn_correspondent_assignment(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        document_progress::spawn_analysis_step(document_id, format!("Correspondent assignment for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for correspondent assignment: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::correspondent_service::CorrespondentService::new(db);
+            if let Err(e) = service.assign_automatically(&document).await {
+                warn!("Correspondent assignment failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Apply the owner's label rules to a freshly processed document in the background
+    fn spawn_label_rules(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        document_progress::spawn_analysis_step(document_id, format!("Label rules for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for label rules: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::label_rule_service::LabelRuleService::new(db);
+            if let Err(e) = service.apply_after_ocr(&document).await {
+                warn!("Label rules failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Extract invoice data in the background when the document looks like an invoice
+    fn spawn_invoice_extraction(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        document_progress::spawn_analysis_step(document_id, format!("Invoice extraction for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for invoice extraction: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::invoice_extraction_service::InvoiceExtractionService::new(db);
+            if let Err(e) = service.extract_after_ocr(&document).await {
+                warn!("Invoice extraction failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Look for when the document expires or renews in the background
+    fn spawn_expiration_extraction(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        document_progress::spawn_analysis_step(document_id, format!("Expiration extraction for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for expiration extraction: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::expiration_service::ExpirationService::new(db);
+            if let Err(e) = service.extract(&document).await {
+                warn!("Expiration extraction failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Name the document after its contents in the background when it has a scanner name
+    fn spawn_title_generation(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        document_progress::spawn_analysis_step(document_id, format!("Title generation for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for title generation: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::title_service::TitleService::new(db);
+            if let Err(e) = service.retitle_after_ocr(&document).await {
+                warn!("Title generation failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Look for personal data in the new OCR text in the background
+    fn spawn_pii_scan(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        document_progress::spawn_analysis_step(document_id, format!("Personal data scan for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for personal data scan: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::pii_service::PiiService::new(db);
+            if let Err(e) = service.scan_after_ocr(&document).await {
+                warn!("Personal data scan failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Tell the post-consume hooks about the document, now that its text is known
+    fn spawn_post_consume_hooks(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        document_progress::spawn_analysis_step(document_id, format!("Post-consume hooks for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for post-consume hooks: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::ingestion_hook_service::IngestionHookService::new(db);
+            if let Err(e) = service.run_post_consume(&document).await {
+                warn!("Post-consume hooks failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Check the signatures of a processed PDF again, flagging them when a change broke them
+    fn spawn_signature_check(&self, document_id: Uuid) {
+        let db = self.db.clone();
+        let file_service = (*self.file_service).clone();
+        document_progress::spawn_analysis_step(document_id, format!("Signature check for document {}", document_id), async move {
+            let document = match db.get_document_by_id(document_id, Uuid::nil(), crate::models::UserRole::Admin).await {
+                Ok(Some(document)) => document,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load document {} for signature check: {}", document_id, e);
+                    return;
+                }
+            };
+            let service = crate::services::pdf_signature_service::PdfSignatureService::new(db, file_service);
+            if let Err(e) = service.recheck_after_ocr(&document).await {
+                warn!("Signature check failed for document {}: {}", document_id, e);
+            }
+        });
+    }
+
     /// Pause OCR processing
     pub fn pause(&self) {
         self.is_paused.store(true, Ordering::SeqCst);
@@ -623,25 +1175,107 @@ impl OcrQueueService {
         self.is_paused.load(Ordering::SeqCst)
     }
 
+    /// Identifier this server uses when claiming queue items
+    pub fn worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    /// Number of OCR jobs this server may run at the same time
+    pub fn max_concurrent_jobs(&self) -> usize {
+        self.max_concurrent_jobs.load(Ordering::SeqCst)
+    }
+
+    /// Number of OCR jobs currently running on this server
+    pub fn active_jobs(&self) -> usize {
+        let retiring = *self.retiring_slots.lock().unwrap_or_else(|e| e.into_inner());
+        (self.max_concurrent_jobs() + retiring).saturating_sub(self.job_slots.available_permits())
+    }
+
+    /// Change the number of concurrent OCR jobs without restarting the worker.
+    /// Lowering the limit lets running jobs finish; their slots are retired as they free up.
+    pub fn set_max_concurrent_jobs(&self, jobs: usize) -> usize {
+        let jobs = jobs.clamp(1, MAX_CONCURRENT_OCR_JOBS);
+        let mut retiring = self.retiring_slots.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = self.max_concurrent_jobs.swap(jobs, Ordering::SeqCst);
+
+        if jobs > previous {
+            // Slots not retired yet are kept rather than adding new ones
+            let kept = (jobs - previous).min(*retiring);
+            *retiring -= kept;
+            self.job_slots.add_permits(jobs - previous - kept);
+        } else if jobs < previous {
+            *retiring += previous - jobs;
+            while *retiring > 0 {
+                let Ok(permit) = self.job_slots.try_acquire() else {
+                    break;
+                };
+                permit.forget();
+                *retiring -= 1;
+            }
+            if *retiring > 0 {
+                tokio::spawn(retire_job_slots(self.job_slots.clone(), self.retiring_slots.clone()));
+            }
+        }
+        drop(retiring);
+
+        info!(
+            "OCR worker {} concurrency changed from {} to {}",
+            self.worker_id, previous, jobs
+        );
+        jobs
+    }
+
+    /// How long ago the worker loop last went round, or None when it never started.
+    /// The loop goes round at least every few seconds unless every job slot is busy.
+    pub fn heartbeat_age(&self) -> Option<Duration> {
+        let heartbeat = self.heartbeat.load(Ordering::Relaxed);
+        if heartbeat == 0 {
+            return None;
+        }
+        let age = (Utc::now().timestamp() - heartbeat).max(0);
+        Some(Duration::from_secs(age as u64))
+    }
+
+    /// Throughput counters of this server's worker since startup
+    pub fn worker_throughput(&self) -> WorkerThroughput {
+        let completed = self.throughput.completed.load(Ordering::Relaxed);
+        let total_ms = self.throughput.total_processing_ms.load(Ordering::Relaxed);
+
+        WorkerThroughput {
+            worker_id: self.worker_id.clone(),
+            max_concurrent_jobs: self.max_concurrent_jobs(),
+            active_jobs: self.active_jobs(),
+            completed,
+            failed: self.throughput.failed.load(Ordering::Relaxed),
+            avg_processing_time_ms: (completed > 0).then(|| total_ms as f64 / completed as f64),
+        }
+    }
+
     /// Start the worker loop
     pub async fn start_worker(self: Arc<Self>) -> Result<()> {
-        let semaphore = Arc::new(Semaphore::new(self.max_concurrent_jobs));
         let ocr_service = Arc::new(EnhancedOcrService::new("/tmp".to_string(), (*self.file_service).clone()));
         
         info!(
             "Starting OCR worker {} with {} concurrent jobs",
-            self.worker_id, self.max_concurrent_jobs
+            self.worker_id, self.max_concurrent_jobs()
         );
         
         crate::debug_log!("OCR_WORKER", 
             "worker_id" => &self.worker_id,
-            "max_concurrent_jobs" => self.max_concurrent_jobs,
+            "max_concurrent_jobs" => self.max_concurrent_jobs(),
             "message" => "OCR worker loop starting"
         );
 
         loop {
-            // Check if processing is paused
-            if self.is_paused() {
+            self.heartbeat.store(Utc::now().timestamp(), Ordering::Relaxed);
+
+            if shutdown::requested() {
+                info!("OCR worker {} takes no more jobs, shutting down", self.worker_id);
+                return Ok(());
+            }
+
+            // Check if processing is paused, here or by an admin for all nodes
+            if self.is_paused() || maintenance_service::holds(QueueKind::Ocr) {
                 crate::debug_log!("OCR_WORKER", 
                     "worker_id" => &self.worker_id,
                     "message" => "OCR processing is paused, waiting..."
@@ -667,7 +1301,12 @@ impl OcrQueueService {
                         "message" => "Dequeued job, spawning processing task"
                     );
                     
-                    let permit = semaphore.clone().acquire_owned().await?;
+                    let permit = self.job_slots.clone().acquire_owned().await?;
+                    if shutdown::requested() {
+                        self.release_item(item.id).await?;
+                        continue;
+                    }
+                    let in_flight = shutdown::track();
                     let self_clone = self.clone();
                     let ocr_service_clone = ocr_service.clone();
                     
@@ -676,9 +1315,37 @@ impl OcrQueueService {
                         // Acquire throttling permit to prevent overwhelming the database
                         match self_clone.processing_throttler.acquire_permit().await {
                             Ok(_throttle_permit) => {
-                                // Process the item with both semaphore and throttle permits held
-                                if let Err(e) = self_clone.process_item(item, &ocr_service_clone).await {
-                                    error!("Error processing OCR item: {}", e);
+                                // Process the item with both semaphore and throttle permits held.
+                                // A panic fails the job instead of leaving it in 'processing'.
+                                let (job_id, document_id) = (item.id, item.document_id);
+                                let parameters = serde_json::json!({
+                                    "job_id": job_id,
+                                    "document_id": document_id,
+                                    "priority": item.priority,
+                                    "attempts": item.attempts,
+                                    "file_size": item.file_size,
+                                });
+                                let context = format!("OCR job {} (document {})", job_id, document_id);
+                                let started = std::time::Instant::now();
+                                let span = tracing::info_span!("ocr.job", %job_id, %document_id);
+                                let job = self_clone.process_item(item, &ocr_service_clone).instrument(span);
+                                let outcome = catch_panic(context, processing_usage::for_document(document_id, job)).await;
+                                crate::monitoring::prometheus::observe_ocr_job(matches!(outcome, Ok(Ok(()))), started.elapsed());
+                                crate::monitoring::slow_operations::record(
+                                    crate::monitoring::slow_operations::OperationCategory::Ocr,
+                                    "ocr.job",
+                                    started,
+                                    matches!(outcome, Ok(Ok(()))),
+                                    parameters,
+                                );
+                                match outcome {
+                                    Ok(Ok(())) => {}
+                                    Ok(Err(e)) => error!("Error processing OCR item {} (document {}): {}", job_id, document_id, e),
+                                    Err(panic) => {
+                                        if let Err(mark_err) = self_clone.mark_failed(job_id, &format!("Internal error: {}", panic.message)).await {
+                                            error!("Failed to mark item {} as failed after panic: {}", job_id, mark_err);
+                                        }
+                                    }
                                 }
                                 // Permits are automatically released when dropped
                             }
@@ -691,6 +1358,7 @@ impl OcrQueueService {
                             }
                         }
                         drop(permit);
+                        drop(in_flight);
                     });
                 }
                 Ok(None) => {
@@ -742,7 +1410,7 @@ impl OcrQueueService {
             .and_then(|s| s.to_str())
             .unwrap_or("jpg");
         
-        let permanent_filename = format!("{}_processed_{}.{}", document_id, chrono::Utc::now().timestamp(), extension);
+        let permanent_filename = format!("{}_processed_{}.{}", document_id, Utc::now().timestamp(), extension);
         let permanent_path = processed_images_dir.join(&permanent_filename);
         
         // Verify source file exists before copying
@@ -1120,10 +1788,20 @@ impl OcrQueueService {
                 started_at = NULL,
                 worker_id = NULL
             WHERE status = 'processing'
-              AND started_at < NOW() - INTERVAL '1 minute' * $1
+              AND (
+                  -- Items of live nodes other than this one are theirs to finish
+                  (started_at < NOW() - INTERVAL '1 minute' * $1
+                   AND (worker_id IS NULL OR worker_id = $2 OR worker_id NOT IN (
+                       SELECT ocr_worker_id FROM live_worker_nodes WHERE ocr_worker_id IS NOT NULL)))
+                  -- Items of nodes that stopped reporting are taken back straight away
+                  OR worker_id IN (
+                      SELECT ocr_worker_id FROM worker_nodes
+                      WHERE ocr_worker_id IS NOT NULL AND id NOT IN (SELECT id FROM live_worker_nodes))
+              )
             "#
         )
         .bind(stale_minutes)
+        .bind(&self.worker_id)
         .execute(&self.pool)
         .await?;
 
@@ -1189,7 +1867,7 @@ impl OcrQueueService {
                 ocr_processing_time_ms: None,
                 failure_reason: failure_reason.to_string(),
                 failure_stage: "ocr".to_string(),
-                existing_document_id: None,
+                existing_document_id: Some(document_id),
                 ingestion_source: "ocr_queue".to_string(),
                 error_message: Some(error_message.to_string()),
                 retry_count: Some(retry_count),
@@ -1230,4 +1908,103 @@ impl OcrQueueService {
             ("other", false)  // Fallback for any unrecognized errors
         }
     }
-}
\ No newline at end of file
+}
+
+/// Retire busy job slots as their jobs finish, until none are left to retire. A slot
+/// freed after the limit was raised again goes back to the workers.
+async fn retire_job_slots(job_slots: Arc<Semaphore>, retiring_slots: Arc<std::sync::Mutex<usize>>) {
+    while let Ok(permit) = job_slots.acquire().await {
+        let mut retiring = retiring_slots.lock().unwrap_or_else(|e| e.into_inner());
+        if *retiring == 0 {
+            return;
+        }
+        permit.forget();
+        *retiring -= 1;
+        if *retiring == 0 {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    /// Let the task retiring job slots catch up with released permits
+    async fn settle() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+
+    #[tokio::test]
+    async fn test_set_max_concurrent_jobs_is_clamped() {
+        let ctx = TestContext::new().await;
+        let queue = &ctx.state.queue_service;
+
+        assert_eq!(queue.set_max_concurrent_jobs(0), 1);
+        assert_eq!(queue.max_concurrent_jobs(), 1);
+        assert_eq!(queue.set_max_concurrent_jobs(MAX_CONCURRENT_OCR_JOBS + 10), MAX_CONCURRENT_OCR_JOBS);
+        assert_eq!(queue.job_slots.available_permits(), MAX_CONCURRENT_OCR_JOBS);
+        assert_eq!(queue.active_jobs(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_lowering_the_limit_retires_slots_as_jobs_finish() {
+        let ctx = TestContext::new().await;
+        let queue = &ctx.state.queue_service;
+        queue.set_max_concurrent_jobs(4);
+
+        let mut running: Vec<_> = (0..3)
+            .map(|_| queue.job_slots.clone().try_acquire_owned().unwrap())
+            .collect();
+        assert_eq!(queue.active_jobs(), 3);
+
+        // The idle slot goes at once, the busy ones as their jobs finish
+        queue.set_max_concurrent_jobs(1);
+        assert_eq!(queue.job_slots.available_permits(), 0);
+        assert_eq!(queue.active_jobs(), 3);
+
+        running.pop();
+        settle().await;
+        assert_eq!(queue.job_slots.available_permits(), 0);
+        assert_eq!(queue.active_jobs(), 2);
+
+        running.pop();
+        settle().await;
+        assert_eq!(*queue.retiring_slots.lock().unwrap(), 0);
+        assert_eq!(queue.job_slots.available_permits(), 0);
+        assert_eq!(queue.active_jobs(), 1);
+
+        running.pop();
+        settle().await;
+        assert_eq!(queue.job_slots.available_permits(), 1);
+        assert_eq!(queue.active_jobs(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_raising_the_limit_keeps_slots_waiting_to_be_retired() {
+        let ctx = TestContext::new().await;
+        let queue = &ctx.state.queue_service;
+        queue.set_max_concurrent_jobs(4);
+
+        let running: Vec<_> = (0..3)
+            .map(|_| queue.job_slots.clone().try_acquire_owned().unwrap())
+            .collect();
+        queue.set_max_concurrent_jobs(1);
+        assert_eq!(*queue.retiring_slots.lock().unwrap(), 2);
+
+        queue.set_max_concurrent_jobs(3);
+        assert_eq!(*queue.retiring_slots.lock().unwrap(), 0);
+        assert_eq!(queue.job_slots.available_permits(), 0);
+        assert_eq!(queue.active_jobs(), 3);
+
+        // Every slot freed now goes back to the workers
+        drop(running);
+        settle().await;
+        assert_eq!(queue.job_slots.available_permits(), 3);
+        assert_eq!(queue.active_jobs(), 0);
+    }
+}
@@ -0,0 +1,173 @@
+//! Cleanup of OCR output before it is stored and indexed, so searches match words
+//! the way they were printed rather than the way the scan broke them up.
+//!
+//! Words hyphenated across a line break are joined, typographic ligatures are spelled
+//! out, odd whitespace is made plain, and letters mistaken for digits inside numbers
+//! (`l`/`I` for 1, `O`/`o` for 0) are read as digits. The raw text is kept alongside.
+
+use super::PAGE_BREAK;
+
+/// Characters that end a line in place of a hyphen: the hyphen, the Unicode hyphen
+/// and the not sign Tesseract often reads a hyphen as
+const LINE_END_HYPHENS: [char; 3] = ['-', '\u{2010}', '¬'];
+
+/// Whether OCR text is normalized before it is stored, from `OCR_TEXT_NORMALIZATION`
+pub fn enabled() -> bool {
+    std::env::var("OCR_TEXT_NORMALIZATION")
+        .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+        .unwrap_or(true)
+}
+
+/// The text with ligatures spelled out, whitespace normalized, hyphenated words
+/// joined and numbers read as digits. Page breaks are kept.
+pub fn normalize_ocr_text(text: &str) -> String {
+    text.split(PAGE_BREAK).map(normalize_page).collect::<Vec<_>>().join(&PAGE_BREAK.to_string())
+}
+
+fn normalize_page(page: &str) -> String {
+    let lines: Vec<String> = expand_ligatures(page).lines().map(normalize_whitespace).collect();
+    let lines = join_hyphenated(lines);
+
+    let mut out = String::with_capacity(page.len());
+    let mut blank_lines = 0;
+    for line in lines {
+        if line.is_empty() {
+            blank_lines += 1;
+            // Keep paragraph breaks, but no more than one empty line in a row
+            if blank_lines > 1 || out.is_empty() {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&fix_numbers(&line));
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
+fn expand_ligatures(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            'ﬀ' => out.push_str("ff"),
+            'ﬁ' => out.push_str("fi"),
+            'ﬂ' => out.push_str("fl"),
+            'ﬃ' => out.push_str("ffi"),
+            'ﬄ' => out.push_str("ffl"),
+            'ﬅ' | 'ﬆ' => out.push_str("st"),
+            // Soft hyphens and zero-width characters only split words apart
+            '\u{00ad}' | '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{feff}' => {}
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// One line with every run of spaces, tabs and other blanks made a single space
+fn normalize_whitespace(line: &str) -> String {
+    line.split(char::is_whitespace).filter(|word| !word.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+/// Join words hyphenated at the end of a line with their rest on the next line,
+/// moving the rest up so the lines stay otherwise as they were
+fn join_hyphenated(lines: Vec<String>) -> Vec<String> {
+    let mut lines = lines.into_iter();
+    let mut out = Vec::new();
+    let Some(mut line) = lines.next() else {
+        return out;
+    };
+    for next in lines {
+        match join_word(&line, &next) {
+            // The rest was all the next line had; the joined word may be hyphenated again
+            Some((joined, remainder)) if remainder.is_empty() => line = joined,
+            Some((joined, remainder)) => {
+                out.push(joined);
+                line = remainder;
+            }
+            None => out.push(std::mem::replace(&mut line, next)),
+        }
+    }
+    out.push(line);
+    out
+}
+
+/// The line with the word it breaks off completed, and what is left of the next line.
+/// A hyphen before an uppercase letter or a digit is kept, as in `Baden-\nWürttemberg`.
+fn join_word(line: &str, next: &str) -> Option<(String, String)> {
+    let stem = line.strip_suffix(LINE_END_HYPHENS)?;
+    if !stem.chars().last().is_some_and(char::is_alphabetic) || !next.chars().next().is_some_and(char::is_lowercase) {
+        return None;
+    }
+    let (rest, remainder) = next.split_once(' ').unwrap_or((next, ""));
+    Some((format!("{}{}", stem, rest), remainder.to_string()))
+}
+
+/// Letters Tesseract confuses with the digit they stand for
+fn digit_for(c: char) -> Option<char> {
+    match c {
+        'l' | 'I' | '|' => Some('1'),
+        'O' | 'o' => Some('0'),
+        _ => None,
+    }
+}
+
+/// Read letters that stand for digits as digits, in words that are otherwise numbers
+/// such as `2O24`, `l5.00` or `1O/O3`
+fn fix_numbers(line: &str) -> String {
+    line.split(' ').map(fix_number).collect::<Vec<_>>().join(" ")
+}
+
+fn fix_number(word: &str) -> String {
+    // Leave punctuation around the number, such as a currency sign or a closing comma
+    let start = word.find(|c: char| c.is_alphanumeric() || c == '|').unwrap_or(word.len());
+    let end = word.rfind(|c: char| c.is_alphanumeric() || c == '|').map_or(start, |i| i + 1);
+    let core = &word[start..end];
+
+    let has_digit = core.chars().any(|c| c.is_ascii_digit());
+    let confused = core.chars().any(|c| digit_for(c).is_some());
+    let numeric = core.chars().all(|c| c.is_ascii_digit() || digit_for(c).is_some() || matches!(c, '.' | ',' | '/' | ':' | '-'));
+    // Without a real digit, words like `lo` or `IO` stay words
+    if !confused || !has_digit || !numeric {
+        return word.to_string();
+    }
+
+    let fixed: String = core.chars().map(|c| digit_for(c).unwrap_or(c)).collect();
+    format!("{}{}{}", &word[..start], fixed, &word[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dehyphenation() {
+        assert_eq!(normalize_ocr_text("the docu-\nment was signed"), "the document\nwas signed");
+        assert_eq!(normalize_ocr_text("an agree¬\nment\nfollows"), "an agreement\nfollows");
+        assert_eq!(normalize_ocr_text("docu-\nmen-\ntation"), "documentation");
+        // Compound words with a capital and hyphens before numbers are kept
+        assert_eq!(normalize_ocr_text("Baden-\nWürttemberg"), "Baden-\nWürttemberg");
+        assert_eq!(normalize_ocr_text("pages 10-\n12"), "pages 10-\n12");
+    }
+
+    #[test]
+    fn test_ligatures_and_whitespace() {
+        assert_eq!(normalize_ocr_text("ﬁnal  oﬀer\u{00a0}for\tthe ﬂat "), "final offer for the flat");
+        assert_eq!(normalize_ocr_text("in\u{00ad}voice"), "invoice");
+        assert_eq!(normalize_ocr_text("\n\nfirst\n\n\n\nsecond\n\n"), "first\n\nsecond");
+        assert_eq!(normalize_ocr_text("page  one\n\u{c}page two\n\u{c}"), "page one\u{c}page two\u{c}");
+    }
+
+    #[test]
+    fn test_number_confusions() {
+        assert_eq!(normalize_ocr_text("Invoice date 1O.O3.2O24"), "Invoice date 10.03.2024");
+        assert_eq!(normalize_ocr_text("Total: €l5.00, due"), "Total: €15.00, due");
+        assert_eq!(normalize_ocr_text("(2O24)"), "(2024)");
+        // Words and codes with other letters stay as they are
+        assert_eq!(normalize_ocr_text("lo IO I O hello A1O"), "lo IO I O hello A1O");
+        assert_eq!(normalize_ocr_text("1OO pages"), "100 pages");
+    }
+}
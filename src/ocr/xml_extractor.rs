@@ -352,13 +352,16 @@ impl XmlOfficeExtractor {
         Ok(())
     }
     
-    /// Try to execute an external tool with timeout and proper error handling
+    /// Try to execute an external tool in the converter sandbox, with timeout and proper
+    /// error handling
     async fn try_external_tool(&self, tool_name: &str, args: &[&str], file_path: &str) -> Result<String> {
-        use tokio::process::Command;
+        use crate::services::converter_sandbox::{ConverterError, ConverterLimit, SandboxedCommand};
         
         // Create the command with proper argument passing (no shell)
-        let mut cmd = Command::new(tool_name);
-        cmd.args(args);
+        let mut cmd = SandboxedCommand::new(tool_name).args(args);
+        if let Ok(file_hash) = crate::services::converter_sandbox::hash_file(std::path::Path::new(file_path)).await {
+            cmd = cmd.input_hash(file_hash);
+        }
         
         // Set timeout (30 seconds should be reasonable for DOC extraction)
         let timeout_duration = Duration::from_secs(30);
@@ -366,9 +369,9 @@ impl XmlOfficeExtractor {
         info!("Executing external tool: {} with args: {:?}", tool_name, args);
         
         // Execute the command with timeout
-        let output = match timeout(timeout_duration, cmd.output()).await {
-            Ok(Ok(output)) => output,
-            Ok(Err(e)) => {
+        let output = match cmd.timeout(timeout_duration).output().await {
+            Ok(output) => output,
+            Err(ConverterError::Spawn { source: e, .. }) => {
                 if e.kind() == std::io::ErrorKind::NotFound {
                     return Err(anyhow!(
                         "Tool '{}' not found. Please install it: sudo apt-get install {}",
@@ -384,12 +387,13 @@ impl XmlOfficeExtractor {
                     return Err(anyhow!("Failed to execute '{}': {}", tool_name, e));
                 }
             }
-            Err(_) => {
+            Err(ConverterError::Killed { limit: ConverterLimit::Time, .. }) => {
                 return Err(anyhow!(
                     "Tool '{}' timed out after 30 seconds while processing '{}'",
                     tool_name, file_path
                 ));
             }
+            Err(e) => return Err(anyhow!("{} while processing '{}'", e, file_path)),
         };
         
         // Check exit status
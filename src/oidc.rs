@@ -26,6 +26,9 @@ pub struct OidcUserInfo {
     pub email: Option<String>,
     pub name: Option<String>,
     pub preferred_username: Option<String>,
+    /// Every other claim, such as the groups used for role and group mapping
+    #[serde(flatten)]
+    pub claims: serde_json::Map<String, serde_json::Value>,
 }
 
 // Storage for PKCE verifiers (csrf_token -> (verifier, expiry))
@@ -121,6 +124,13 @@ impl OidcClient {
             .add_scope(Scope::new("email".to_string()))
             .add_scope(Scope::new("profile".to_string()));
 
+        // Some providers only include group claims when asked, e.g. with a `groups` scope
+        if let Ok(extra_scopes) = std::env::var("OIDC_EXTRA_SCOPES") {
+            for scope in extra_scopes.split([' ', ',']).filter(|s| !s.is_empty()) {
+                auth_request = auth_request.add_scope(Scope::new(scope.to_string()));
+            }
+        }
+
         // For public clients (no client_secret), PKCE is required for security
         // For confidential clients, PKCE is optional but we don't use it to avoid state management
         if self.is_public_client {
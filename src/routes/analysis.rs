@@ -0,0 +1,259 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        AnalysisBatch, AnalysisBatchRequest, AnalysisCostEstimate, NodeRole, UserRole, MAX_ANALYSIS_BATCH_DOCUMENTS,
+    },
+    scheduling::cluster,
+    services::analysis_batch_service::AnalysisBatchService,
+    services::llm::llm_service::{LLMService, GRAPH_EXTRACTION_MAX_CHARS},
+    AppState,
+};
+
+/// Analysis batches listed per request
+const ANALYSIS_BATCH_LIST_LIMIT: i64 = 50;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/batch", get(list_analysis_batches).post(create_analysis_batch))
+        .route("/batch/estimate", post(estimate_analysis_batch))
+        .route("/batch/{id}", get(get_analysis_batch))
+        .route("/batch/{id}/cancel", post(cancel_analysis_batch))
+}
+
+/// The matching documents and what analyzing them would cost
+async fn resolve(
+    state: &AppState,
+    auth_user: &AuthUser,
+    request: &AnalysisBatchRequest,
+) -> Result<(Vec<Uuid>, AnalysisCostEstimate), StatusCode> {
+    if let Err(e) = request.validate() {
+        debug!("Rejected analysis batch: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to find documents to analyze: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let document_ids = state
+        .db
+        .find_analysis_batch_documents(
+            auth_user.user.id,
+            auth_user.user.role,
+            &request.filter,
+            MAX_ANALYSIS_BATCH_DOCUMENTS as i64 + 1,
+        )
+        .await
+        .map_err(db_error)?;
+    let sent_chars = state
+        .db
+        .count_analysis_text_chars(&document_ids, GRAPH_EXTRACTION_MAX_CHARS as i32)
+        .await
+        .map_err(db_error)?;
+
+    let llm = LLMService::new(state.db.get_pool().clone());
+    let estimate = AnalysisCostEstimate::new(document_ids.len() as i64, sent_chars, llm.model(), llm.token_prices());
+    Ok((document_ids, estimate))
+}
+
+/// Estimate tokens and cost of analyzing the documents matching a filter, without
+/// starting anything
+#[utoipa::path(
+    post,
+    path = "/api/analysis/batch/estimate",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = AnalysisBatchRequest,
+    responses(
+        (status = 200, description = "Documents that would be analyzed and their estimated cost", body = AnalysisCostEstimate),
+        (status = 400, description = "Invalid filter or concurrency"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn estimate_analysis_batch(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<AnalysisBatchRequest>,
+) -> Result<Json<AnalysisCostEstimate>, StatusCode> {
+    let (_, estimate) = resolve(&state, &auth_user, &request).await?;
+    Ok(Json(estimate))
+}
+
+/// Start knowledge-graph extraction for every document matching a filter, in the
+/// background
+#[utoipa::path(
+    post,
+    path = "/api/analysis/batch",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = AnalysisBatchRequest,
+    responses(
+        (status = 202, description = "Batch queued with its cost estimate; follow it at /api/analysis/batch/{id}", body = AnalysisBatch),
+        (status = 400, description = "Invalid filter or concurrency, no matching documents, or too many"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn create_analysis_batch(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<AnalysisBatchRequest>,
+) -> Result<(StatusCode, Json<AnalysisBatch>), StatusCode> {
+    let (document_ids, estimate) = resolve(&state, &auth_user, &request).await?;
+    if document_ids.is_empty() || estimate.exceeds_limit {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let batch = state
+        .db
+        .create_analysis_batch(auth_user.user.id, &request.filter, request.concurrency(), &document_ids, &estimate)
+        .await
+        .map_err(|e| {
+            error!("Failed to start analysis batch: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!(
+        "User {} started analysis batch {} on {} documents",
+        auth_user.user.id, batch.id, batch.total_documents
+    );
+    // Nodes without the analysis role leave the batch to the nodes that have it
+    if cluster::runs(NodeRole::Analysis) {
+        match state.db.claim_analysis_batch(batch.id, cluster::local_node().id).await {
+            Ok(true) => AnalysisBatchService::new(state.db.clone()).spawn(batch.id),
+            Ok(false) => {}
+            Err(e) => warn!("Failed to claim analysis batch {}, leaving it to the analysis nodes: {}", batch.id, e),
+        }
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(batch)))
+}
+
+/// The user's analysis batches, newest first
+#[utoipa::path(
+    get,
+    path = "/api/analysis/batch",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Recent analysis batches with their progress", body = Vec<AnalysisBatch>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_analysis_batches(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<AnalysisBatch>>, StatusCode> {
+    let batches = state
+        .db
+        .list_analysis_batches(auth_user.user.id, ANALYSIS_BATCH_LIST_LIMIT)
+        .await
+        .map_err(|e| {
+            error!("Failed to list analysis batches: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(batches))
+}
+
+/// The batch, if the user started it or is an admin
+async fn visible_batch(state: &AppState, auth_user: &AuthUser, id: Uuid) -> Result<AnalysisBatch, StatusCode> {
+    let batch = state
+        .db
+        .get_analysis_batch(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get analysis batch {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if batch.user_id != auth_user.user.id && auth_user.user.role != UserRole::Admin {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(batch)
+}
+
+/// Status and progress of an analysis batch
+#[utoipa::path(
+    get,
+    path = "/api/analysis/batch/{id}",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Analysis batch ID")
+    ),
+    responses(
+        (status = 200, description = "Analysis batch", body = AnalysisBatch),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Analysis batch not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_analysis_batch(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AnalysisBatch>, StatusCode> {
+    Ok(Json(visible_batch(&state, &auth_user, id).await?))
+}
+
+/// Stop an analysis batch. Documents being analyzed finish; graphs already extracted
+/// are kept.
+#[utoipa::path(
+    post,
+    path = "/api/analysis/batch/{id}/cancel",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Analysis batch ID")
+    ),
+    responses(
+        (status = 200, description = "Cancelled batch", body = AnalysisBatch),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Analysis batch not found"),
+        (status = 409, description = "The batch has already ended"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn cancel_analysis_batch(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AnalysisBatch>, StatusCode> {
+    visible_batch(&state, &auth_user, id).await?;
+    let batch = state
+        .db
+        .cancel_analysis_batch(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to cancel analysis batch {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    info!("User {} cancelled analysis batch {}", auth_user.user.id, id);
+    Ok(Json(batch))
+}
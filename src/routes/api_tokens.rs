@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::{generate_api_token, hash_api_token, AuthUser},
+    models::{ApiToken, CreateApiTokenRequest, CreateApiTokenResponse},
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    AppState,
+};
+
+/// Longest allowed lifetime of a token
+const MAX_EXPIRY_DAYS: i64 = 3650;
+/// Tokens one user may hold at a time
+const MAX_TOKENS_PER_USER: usize = 50;
+
+fn is_valid_token_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty() && name.len() <= 100
+}
+
+/// List the current user's API tokens
+#[utoipa::path(
+    get,
+    path = "/api/users/me/tokens",
+    tag = "api_tokens",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "API tokens of the current user, newest first", body = Vec<ApiToken>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "API tokens cannot manage API tokens"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_api_tokens(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<ApiToken>>, StatusCode> {
+    let tokens = state.db.get_api_tokens(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to list API tokens of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(tokens))
+}
+
+/// Create an API token for the current user
+///
+/// The response holds the only copy of the secret; the server keeps just its hash.
+#[utoipa::path(
+    post,
+    path = "/api/users/me/tokens",
+    tag = "api_tokens",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateApiTokenRequest,
+    responses(
+        (status = 201, description = "Token created", body = CreateApiTokenResponse),
+        (status = 400, description = "Empty or too long name, or invalid expiry"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "API tokens cannot manage API tokens"),
+        (status = 409, description = "The user already has the maximum number of tokens"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_api_token(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<CreateApiTokenRequest>,
+) -> Result<(StatusCode, Json<CreateApiTokenResponse>), StatusCode> {
+    if !is_valid_token_name(&request.name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if request.expires_in_days.is_some_and(|days| !(1..=MAX_EXPIRY_DAYS).contains(&days)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let existing = state.db.get_api_tokens(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to list API tokens of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if existing.len() >= MAX_TOKENS_PER_USER {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let (secret, prefix) = generate_api_token();
+    let token = state
+        .db
+        .create_api_token(
+            auth_user.user.id,
+            &request.name,
+            &prefix,
+            &hash_api_token(&secret),
+            request.scope,
+            request.expires_in_days.map(|days| Utc::now() + Duration::days(days)),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create API token for user {}: {}", auth_user.user.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::API_TOKEN_CREATE, "user", Some(auth_user.user.id)).details(json!({
+            "api_token_id": token.id,
+            "name": token.name,
+            "scope": token.scope,
+            "expires_at": token.expires_at,
+        })),
+    )
+    .await;
+
+    info!("User {} created API token {} ({:?})", auth_user.user.id, token.id, token.scope);
+    Ok((StatusCode::CREATED, Json(CreateApiTokenResponse { token, secret })))
+}
+
+/// Revoke one of the current user's API tokens
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/tokens/{id}",
+    tag = "api_tokens",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "API token ID")
+    ),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "API tokens cannot manage API tokens"),
+        (status = 404, description = "Token not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_api_token(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state.db.delete_api_token(id, auth_user.user.id).await.map_err(|e| {
+        error!("Failed to delete API token {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::API_TOKEN_REVOKE, "user", Some(auth_user.user.id))
+            .details(json!({ "api_token_id": id })),
+    )
+    .await;
+
+    info!("User {} revoked API token {}", auth_user.user.id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
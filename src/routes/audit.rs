@@ -0,0 +1,60 @@
+use axum::{
+    extract::{OriginalUri, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    auth::AuthUser,
+    models::{AuditLogQuery, AuditLogResponse},
+    routes::queue::require_admin,
+    utils::pagination::{Cursor, Page, PageRequest},
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/", get(get_audit_log))
+}
+
+/// Recorded actions matching the filters, newest first
+#[utoipa::path(
+    get,
+    path = "/api/audit",
+    tag = "audit",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(AuditLogQuery),
+    responses(
+        (status = 200, description = "Matching audit log entries, with a `Link` to the next page and `X-Total-Count`", body = AuditLogResponse),
+        (status = 400, description = "Invalid cursor, or a cursor combined with an offset"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_audit_log(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<(HeaderMap, Json<AuditLogResponse>), StatusCode> {
+    require_admin(&auth_user)?;
+
+    let page = PageRequest::parse(query.limit, 50, 500, query.cursor.as_deref(), query.offset, query.include_total)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let (entries, total) = state.db.get_audit_log(&query, &page).await.map_err(|e| {
+        error!("Failed to query audit log: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let page = Page::new(entries, page.limit, total, |entry| Cursor::new(entry.created_at, entry.id));
+    Ok((
+        page.headers(&uri),
+        Json(AuditLogResponse { total: page.total, next_cursor: page.next_cursor, entries: page.items }),
+    ))
+}
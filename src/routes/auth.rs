@@ -9,9 +9,13 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::{
-    auth::{create_jwt, AuthUser},
-    models::{CreateUser, LoginRequest, LoginResponse, User, UserResponse, UserRole},
+    auth::{create_two_factor_challenge, start_session, AuthUser, CHALLENGE_LOGIN, CHALLENGE_SETUP},
+    models::{CreateUser, LoginRequest, LoginResponse, TwoFactorChallenge, User, UserResponse, UserRole},
     oidc::OidcUserInfo,
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        oidc_provisioning_service, rate_limit_service,
+    },
     AppState,
 };
 
@@ -19,6 +23,11 @@ pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
+        .route("/logout", post(crate::routes::sessions::logout))
+        .route("/login/2fa", post(crate::routes::two_factor::login_second_factor))
+        .route("/passkey/start", post(crate::routes::passkeys::start_passkey_login))
+        .route("/passkey/finish", post(crate::routes::passkeys::finish_passkey_login))
+        .nest("/2fa", crate::routes::two_factor::router())
         .route("/me", get(me))
         .route("/config", get(get_auth_config))
         .route("/oidc/login", get(oidc_login))
@@ -116,45 +125,109 @@ async fn get_auth_config(
     request_body = LoginRequest,
     responses(
         (status = 200, description = "Login successful", body = LoginResponse),
+        (status = 202, description = "Password accepted, a second factor or two-factor enrollment is needed", body = TwoFactorChallenge),
         (status = 401, description = "Unauthorized - invalid credentials"),
+        (status = 429, description = "Too many failed logins for this account or address, see Retry-After"),
         (status = 500, description = "Internal server error")
     )
 )]
 async fn login(
     State(state): State<Arc<AppState>>,
+    client: ClientInfo,
     Json(login_data): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, StatusCode> {
+) -> Result<Response, StatusCode> {
     // Check if local authentication is enabled
     if !state.config.allow_local_auth.unwrap_or(true) {
         tracing::warn!("Local authentication attempt rejected - local auth is disabled");
         return Err(StatusCode::FORBIDDEN);
     }
 
-    let user = state
+    let login_failed = |reason: &'static str| {
+        AuditEvent::new(audit_service::LOGIN_FAILED, "user", None)
+            .details(serde_json::json!({ "username": login_data.username, "reason": reason }))
+    };
+
+    if let Err(retry_after) = rate_limit_service::check_account(&state.db, &login_data.username).await {
+        audit_service::record(&state.db, None, &client, login_failed("rate_limited")).await;
+        return Ok(rate_limit_service::too_many_requests(retry_after));
+    }
+
+    let Some(user) = state
         .db
         .get_user_by_username(&login_data.username)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+    else {
+        audit_service::record(&state.db, None, &client, login_failed("unknown_user")).await;
+        rate_limit_service::record_account_failure(&state.db, &login_data.username).await;
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    // OIDC users don't have passwords
+    let Some(password_hash) = user.password_hash.as_ref() else {
+        audit_service::record(&state.db, None, &client, login_failed("no_password")).await;
+        rate_limit_service::record_account_failure(&state.db, &login_data.username).await;
+        return Err(StatusCode::UNAUTHORIZED);
+    };
 
-    let password_hash = user.password_hash
-        .as_ref()
-        .ok_or(StatusCode::UNAUTHORIZED)?; // OIDC users don't have passwords
-        
     let is_valid = bcrypt::verify(&login_data.password, password_hash)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     if !is_valid {
+        audit_service::record(&state.db, None, &client, login_failed("invalid_password")).await;
+        rate_limit_service::record_account_failure(&state.db, &login_data.username).await;
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    let token = create_jwt(&user, &state.config.jwt_secret)
+    // With two-factor login the session is only issued by /api/auth/login/2fa, or by
+    // /api/auth/2fa/enable when the policy requires enrolling first
+    let two_factor_enabled = state
+        .db
+        .get_user_two_factor(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .is_some_and(|two_factor| two_factor.is_enabled());
+    let two_factor_required = !two_factor_enabled
+        && state
+            .db
+            .get_auth_policy()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .require_two_factor;
+
+    if two_factor_enabled || two_factor_required {
+        let purpose = if two_factor_enabled { CHALLENGE_LOGIN } else { CHALLENGE_SETUP };
+        let challenge_token = create_two_factor_challenge(user.id, purpose, &state.config.jwt_secret)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(TwoFactorChallenge {
+                two_factor_required: two_factor_enabled,
+                two_factor_setup_required: two_factor_required,
+                challenge_token,
+            }),
+        )
+            .into_response());
+    }
+
+    let token = start_session(&state, &user, "password", &client)
+        .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    audit_service::record(
+        &state.db,
+        Some(&user),
+        &client,
+        AuditEvent::new(audit_service::LOGIN, "user", Some(user.id))
+            .details(serde_json::json!({ "method": "password" })),
+    )
+    .await;
+
     Ok(Json(LoginResponse {
         token,
         user: user.into(),
-    }))
+    })
+    .into_response())
 }
 
 #[utoipa::path(
@@ -216,6 +289,7 @@ async fn oidc_login(State(state): State<Arc<AppState>>) -> Result<Redirect, Stat
 async fn oidc_callback(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
+    client: ClientInfo,
     Query(params): Query<OidcCallbackQuery>,
 ) -> Result<Redirect, StatusCode> {
     tracing::info!("OIDC callback called with params: code={:?}, state={:?}, error={:?}", 
@@ -345,13 +419,31 @@ async fn oidc_callback(
         }
     };
 
+    // Roles and group memberships follow the IdP groups on every login
+    let user = oidc_provisioning_service::sync_user(&state.db, user, &user_info, &client)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to apply OIDC group mapping to user {}: {}", user_info.sub, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     // Create JWT token
-    let token = create_jwt(&user, &state.config.jwt_secret)
+    let token = start_session(&state, &user, "oidc", &client)
+        .await
         .map_err(|e| {
             tracing::error!("Failed to create JWT token: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    audit_service::record(
+        &state.db,
+        Some(&user),
+        &client,
+        AuditEvent::new(audit_service::LOGIN, "user", Some(user.id))
+            .details(serde_json::json!({ "method": "oidc" })),
+    )
+    .await;
+
     // Redirect to frontend with token in URL fragment
     // The frontend should extract the token and store it
     // Use absolute URL to ensure hash fragment is handled correctly by the browser
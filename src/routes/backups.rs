@@ -0,0 +1,152 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{Backup, BackupOverview},
+    routes::queue::require_admin,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::backup_service::{self, BackupService},
+    AppState,
+};
+
+/// Backups listed per request
+const BACKUP_LIST_LIMIT: i64 = 50;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_backups).post(create_backup))
+        .route("/status", get(get_backup_status))
+}
+
+/// Recent backups, newest first
+#[utoipa::path(
+    get,
+    path = "/api/backups",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Recent backups with their status", body = Vec<Backup>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_backups(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<Backup>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let backups = state.db.list_backups(BACKUP_LIST_LIMIT).await.map_err(|e| {
+        error!("Failed to list backups: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(backups))
+}
+
+/// The backup configuration, the last backup and when the next one is due
+#[utoipa::path(
+    get,
+    path = "/api/backups/status",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Backup configuration and status", body = BackupOverview),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_backup_status(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<BackupOverview>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let overview = match backup_service::configured() {
+        Ok(Some(config)) => BackupService::new(state.clone(), config).overview().await,
+        Ok(None) => backup_service::overview_without_config(&state).await,
+        Err(configuration_error) => backup_service::overview_without_config(&state)
+            .await
+            .map(|overview| BackupOverview { configuration_error: Some(configuration_error), ..overview }),
+    };
+
+    overview.map(Json).map_err(|e| {
+        error!("Failed to get backup status: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Back up now instead of waiting for the schedule
+#[utoipa::path(
+    post,
+    path = "/api/backups",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 202, description = "Backup started; follow it at /api/backups", body = Backup),
+        (status = 400, description = "Backups are not configured"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 409, description = "A backup is already running"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_backup(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+) -> Result<(StatusCode, Json<Backup>), StatusCode> {
+    require_admin(&auth_user)?;
+
+    let config = backup_service::configured()
+        .ok()
+        .flatten()
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let service = BackupService::new(state.clone(), config);
+    let backup = service
+        .start(Some(auth_user.user.id))
+        .await
+        .map_err(|e| {
+            error!("Failed to start backup: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    info!("User {} started backup {}", auth_user.user.id, backup.id);
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LIBRARY_BACKUP, "backup", Some(backup.id))
+            .details(json!({ "destination": backup.destination, "kind": backup.kind })),
+    )
+    .await;
+
+    let started = backup.clone();
+    spawn_guarded(format!("backup {}", backup.id), async move {
+        let backup_id = started.id;
+        if let Err(e) = service.run_backup(started).await {
+            error!("Failed to record backup {}: {}", backup_id, e);
+        }
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(backup)))
+}
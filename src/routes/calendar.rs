@@ -0,0 +1,254 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{Json, Response},
+    routing::get,
+    Router,
+};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tracing::{debug, error, info};
+
+use crate::{
+    auth::AuthUser,
+    models::{CalendarFeed, CreateCalendarFeedResponse, UpdateCalendarFeedRequest},
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        calendar_service,
+        share_link_service::{generate_token, hash_token},
+    },
+    AppState,
+};
+
+/// Fetching feeds, for calendar apps that subscribe without an account
+pub fn public_router() -> Router<Arc<AppState>> {
+    Router::new().route("/{token}", get(get_calendar_feed_ics))
+}
+
+fn feed_url(token: &str) -> String {
+    format!("/api/public/calendar/{}.ics", token)
+}
+
+/// The current user's calendar feed settings
+#[utoipa::path(
+    get,
+    path = "/api/users/me/calendar-feed",
+    tag = "calendar",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Feed settings; the token is not shown again", body = CalendarFeed),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "The user has no feed"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_calendar_feed(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<CalendarFeed>, StatusCode> {
+    let feed = state
+        .db
+        .get_calendar_feed(auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get calendar feed of user {}: {}", auth_user.user.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(feed))
+}
+
+/// Create the current user's calendar feed, or replace its token
+///
+/// The response holds the only copy of the token; the server keeps just its hash. A
+/// new token stops the old URL from working.
+#[utoipa::path(
+    post,
+    path = "/api/users/me/calendar-feed",
+    tag = "calendar",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 201, description = "Feed created or token replaced", body = CreateCalendarFeedResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "API tokens cannot create feed tokens"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_calendar_feed(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+) -> Result<(StatusCode, Json<CreateCalendarFeedResponse>), StatusCode> {
+    let token = generate_token();
+    let feed = state
+        .db
+        .set_calendar_feed_token(auth_user.user.id, &hash_token(&token))
+        .await
+        .map_err(|e| {
+            error!("Failed to create calendar feed for user {}: {}", auth_user.user.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::CALENDAR_FEED_CREATE, "user", Some(auth_user.user.id)),
+    )
+    .await;
+
+    info!("User {} created a calendar feed token", auth_user.user.id);
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateCalendarFeedResponse { url: feed_url(&token), feed, token }),
+    ))
+}
+
+/// Change the reminders and date fields of the current user's calendar feed
+#[utoipa::path(
+    put,
+    path = "/api/users/me/calendar-feed",
+    tag = "calendar",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = UpdateCalendarFeedRequest,
+    responses(
+        (status = 200, description = "Updated feed settings", body = CalendarFeed),
+        (status = 400, description = "Invalid lead times or field names"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "The user has no feed"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_calendar_feed(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateCalendarFeedRequest>,
+) -> Result<Json<CalendarFeed>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected calendar feed update of user {}: {}", auth_user.user.id, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut lead_days = request.lead_days.clone();
+    if let Some(lead_days) = lead_days.as_mut() {
+        lead_days.sort_unstable_by(|a, b| b.cmp(a));
+        lead_days.dedup();
+    }
+    let feed = state
+        .db
+        .update_calendar_feed(auth_user.user.id, lead_days.as_deref(), request.date_fields.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Failed to update calendar feed of user {}: {}", auth_user.user.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(feed))
+}
+
+/// Delete the current user's calendar feed; its URL stops working
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/calendar-feed",
+    tag = "calendar",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Feed deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "The user has no feed"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_calendar_feed(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state.db.delete_calendar_feed(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to delete calendar feed of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::CALENDAR_FEED_REVOKE, "user", Some(auth_user.user.id)),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The iCalendar feed of a token
+///
+/// All-day events for the dates in the feed's custom fields of the user's documents,
+/// from 30 days ago on, each with a reminder per lead time.
+#[utoipa::path(
+    get,
+    path = "/api/public/calendar/{token}",
+    tag = "calendar",
+    params(
+        ("token" = String, Path, description = "Feed token, optionally followed by .ics")
+    ),
+    responses(
+        (status = 200, description = "iCalendar feed", content_type = "text/calendar"),
+        (status = 404, description = "Unknown token"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_calendar_feed_ics(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Response, StatusCode> {
+    let token = token.strip_suffix(".ics").unwrap_or(&token);
+    let feed = state
+        .db
+        .open_calendar_feed(&hash_token(token))
+        .await
+        .map_err(|e| {
+            error!("Failed to look up calendar feed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let now = Utc::now();
+    let since = (now - Duration::days(calendar_service::PAST_DAYS)).date_naive();
+    let entries = state
+        .db
+        .get_calendar_entries(feed.user_id, &feed.date_fields, since)
+        .await
+        .map_err(|e| {
+            error!("Failed to get calendar entries of user {}: {}", feed.user_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let public_url = std::env::var("PUBLIC_URL").ok().filter(|url| !url.trim().is_empty());
+    let calendar = calendar_service::render_feed(&feed, &entries, public_url.as_deref().map(str::trim), now);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+        .header(header::CONTENT_DISPOSITION, "inline; filename=\"readur.ics\"")
+        .header(header::CACHE_CONTROL, "private, max-age=300")
+        .header("X-Robots-Tag", "noindex")
+        .body(Body::from(calendar))
+        .map_err(|e| {
+            error!("Failed to build calendar feed response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
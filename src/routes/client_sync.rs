@@ -0,0 +1,313 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        ClientChangeOutcome, ClientChangeResult, ClientDocumentChange, ClientSyncChangesQuery,
+        ClientSyncChangesResponse, ClientSyncCursor, ClientSyncDocument, ClientSyncPushRequest,
+        ClientSyncPushResponse, ClientSyncTombstone, Document, TombstoneReason,
+    },
+    routes::documents::crud::{apply_rename, is_valid_filename, remove_document},
+    services::audit_service::{self, document_details, AuditEvent, ClientInfo},
+    services::event_service::EventService,
+    AppState,
+};
+
+const DEFAULT_CHANGES_LIMIT: i64 = 500;
+const MAX_CHANGES_LIMIT: i64 = 2000;
+const MAX_PUSH_CHANGES: usize = 500;
+/// Changes younger than this are returned by a later request, see `Database::get_client_sync_changes`
+const SETTLE_SECONDS: i32 = 5;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/changes", get(get_client_sync_changes))
+        .route("/push", post(push_client_sync_changes))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/client-sync/changes",
+    tag = "client_sync",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(ClientSyncChangesQuery),
+    responses(
+        (status = 200, description = "Document changes and tombstones after the cursor", body = ClientSyncChangesResponse),
+        (status = 400, description = "Invalid cursor or label IDs"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_client_sync_changes(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<ClientSyncChangesQuery>,
+) -> Result<Json<ClientSyncChangesResponse>, StatusCode> {
+    let after = match query.cursor.as_deref().filter(|c| !c.is_empty()) {
+        Some(cursor) => Some(ClientSyncCursor::parse(cursor).ok_or(StatusCode::BAD_REQUEST)?),
+        None => None,
+    };
+    let scope = match query.label_ids.as_deref().filter(|ids| !ids.trim().is_empty()) {
+        Some(ids) => Some(
+            ids.split(',')
+                .map(|id| Uuid::parse_str(id.trim()))
+                .collect::<Result<HashSet<Uuid>, _>>()
+                .map_err(|_| StatusCode::BAD_REQUEST)?,
+        ),
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_CHANGES_LIMIT).clamp(1, MAX_CHANGES_LIMIT);
+
+    let mut changes = state
+        .db
+        .get_client_sync_changes(auth_user.user.id, after, SETTLE_SECONDS, limit + 1)
+        .await
+        .map_err(|e| {
+            error!("Failed to read client sync changes: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let has_more = changes.len() as i64 > limit;
+    changes.truncate(limit as usize);
+
+    let changed_ids: Vec<Uuid> = changes.iter().filter(|c| !c.deleted).map(|c| c.document_id).collect();
+    let (mut documents, mut labels) = load_documents(&state, auth_user.user.id, &changed_ids).await.map_err(|e| {
+        error!("Failed to load changed documents: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // A full sync starts from an empty mirror, so it needs no tombstones
+    let incremental = after.is_some();
+    let mut response_documents = Vec::new();
+    let mut tombstones = Vec::new();
+    for change in &changes {
+        if change.deleted {
+            if incremental {
+                tombstones.push(ClientSyncTombstone {
+                    document_id: change.document_id,
+                    reason: TombstoneReason::Deleted,
+                    removed_at: change.changed_at,
+                });
+            }
+            continue;
+        }
+        // Deleted since the changes were read; its tombstone comes with a later request
+        let Some(document) = documents.remove(&change.document_id) else {
+            continue;
+        };
+        let label_ids = labels.remove(&change.document_id).unwrap_or_default();
+        let in_scope = match &scope {
+            Some(scope) => label_ids.iter().any(|id| scope.contains(id)),
+            None => true,
+        };
+        if in_scope {
+            response_documents.push(client_document(document, label_ids));
+        } else if incremental {
+            tombstones.push(ClientSyncTombstone {
+                document_id: change.document_id,
+                reason: TombstoneReason::OutOfScope,
+                removed_at: change.changed_at,
+            });
+        }
+    }
+
+    let cursor = match changes.last() {
+        Some(last) => Some(ClientSyncCursor { changed_at: last.changed_at, document_id: last.document_id }.encode()),
+        None => query.cursor,
+    };
+
+    Ok(Json(ClientSyncChangesResponse {
+        documents: response_documents,
+        tombstones,
+        cursor,
+        has_more,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/client-sync/push",
+    tag = "client_sync",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = ClientSyncPushRequest,
+    responses(
+        (status = 200, description = "Outcome of each change, with the server's version of the document", body = ClientSyncPushResponse),
+        (status = 400, description = "Too many changes in one request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn push_client_sync_changes(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<ClientSyncPushRequest>,
+) -> Result<Json<ClientSyncPushResponse>, StatusCode> {
+    if request.changes.len() > MAX_PUSH_CHANGES {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut results = Vec::with_capacity(request.changes.len());
+    for change in &request.changes {
+        let result = apply_change(&state, &auth_user, &client, change).await.map_err(|e| {
+            error!("Failed to apply client change to document {}: {}", change.document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        results.push(result);
+    }
+
+    Ok(Json(ClientSyncPushResponse { results }))
+}
+
+/// Apply one offline change. Label additions and removals always merge; a rename or
+/// delete only applies when the document is unchanged since the client synced it,
+/// otherwise the server's version wins.
+async fn apply_change(
+    state: &AppState,
+    auth_user: &AuthUser,
+    client: &ClientInfo,
+    change: &ClientDocumentChange,
+) -> anyhow::Result<ClientChangeResult> {
+    let user_id = auth_user.user.id;
+    let outcome = |outcome, message: Option<&str>, document| ClientChangeResult {
+        document_id: change.document_id,
+        outcome,
+        message: message.map(String::from),
+        document,
+    };
+
+    let document = state
+        .db
+        .get_document_by_id(change.document_id, user_id, auth_user.user.role)
+        .await?
+        .filter(|document| document.user_id == user_id);
+    let Some(document) = document else {
+        return Ok(outcome(ClientChangeOutcome::Deleted, None, None));
+    };
+
+    let filename = change.original_filename.as_deref().map(str::trim);
+    if filename.is_some_and(|filename| !is_valid_filename(filename)) {
+        return Ok(outcome(ClientChangeOutcome::Rejected, Some("Invalid filename"), None));
+    }
+    let mut label_ids: Vec<Uuid> = change.add_label_ids.iter().chain(&change.remove_label_ids).copied().collect();
+    label_ids.sort();
+    label_ids.dedup();
+    if !label_ids.is_empty() && state.db.count_assignable_labels(user_id, &label_ids).await? != label_ids.len() as i64 {
+        return Ok(outcome(ClientChangeOutcome::Rejected, Some("Unknown label"), None));
+    }
+
+    let renames = filename.is_some_and(|filename| filename != document.original_filename);
+    if (change.delete || renames) && state.db.is_on_legal_hold(document.id).await? {
+        return Ok(outcome(ClientChangeOutcome::Rejected, Some("Document is on legal hold"), None));
+    }
+
+    let unchanged = document.updated_at == change.base_updated_at;
+    if change.delete {
+        if unchanged {
+            remove_document(state, &document, auth_user).await?;
+            audit_service::record(
+                &state.db,
+                Some(&auth_user.user),
+                client,
+                AuditEvent::new(audit_service::DOCUMENT_DELETE, "document", Some(document.id))
+                    .details(serde_json::json!({ "via": "client_sync", "document": document_details(&document) })),
+            )
+            .await;
+            return Ok(outcome(ClientChangeOutcome::Applied, None, None));
+        }
+        let current = load_document(state, user_id, change.document_id).await?;
+        return Ok(outcome(ClientChangeOutcome::Conflict, Some("Changed on the server since the last sync"), current));
+    }
+
+    let mut conflict = false;
+    if let Some(filename) = filename.filter(|filename| *filename != document.original_filename) {
+        if unchanged {
+            apply_rename(state, &document, filename).await?;
+        } else {
+            conflict = true;
+        }
+    }
+    state.db.add_document_labels(document.id, &change.add_label_ids, user_id).await?;
+    state.db.remove_document_labels(document.id, &change.remove_label_ids).await?;
+    if !change.add_label_ids.is_empty() || !change.remove_label_ids.is_empty() {
+        audit_service::record(
+            &state.db,
+            Some(&auth_user.user),
+            client,
+            AuditEvent::new(audit_service::DOCUMENT_LABELS_CHANGE, "document", Some(document.id)).details(
+                serde_json::json!({
+                    "via": "client_sync",
+                    "added": change.add_label_ids,
+                    "removed": change.remove_label_ids,
+                }),
+            ),
+        )
+        .await;
+        EventService::new(state.db.clone()).publish_labels_changed(document.id).await;
+    }
+
+    let current = load_document(state, user_id, change.document_id).await?;
+    Ok(if conflict {
+        outcome(ClientChangeOutcome::Conflict, Some("Renamed on the server since the last sync"), current)
+    } else {
+        outcome(ClientChangeOutcome::Applied, None, current)
+    })
+}
+
+/// The user's documents among `document_ids`, with their label IDs
+async fn load_documents(
+    state: &AppState,
+    user_id: Uuid,
+    document_ids: &[Uuid],
+) -> anyhow::Result<(HashMap<Uuid, Document>, HashMap<Uuid, Vec<Uuid>>)> {
+    if document_ids.is_empty() {
+        return Ok((HashMap::new(), HashMap::new()));
+    }
+    let documents = state
+        .db
+        .get_documents_by_ids(document_ids)
+        .await?
+        .into_iter()
+        .filter(|document| document.user_id == user_id)
+        .map(|document| (document.id, document))
+        .collect();
+    let labels = state.db.get_document_label_ids(document_ids).await?;
+    Ok((documents, labels))
+}
+
+async fn load_document(state: &AppState, user_id: Uuid, document_id: Uuid) -> anyhow::Result<Option<ClientSyncDocument>> {
+    let (mut documents, mut labels) = load_documents(state, user_id, &[document_id]).await?;
+    Ok(documents
+        .remove(&document_id)
+        .map(|document| client_document(document, labels.remove(&document_id).unwrap_or_default())))
+}
+
+fn client_document(document: Document, label_ids: Vec<Uuid>) -> ClientSyncDocument {
+    ClientSyncDocument {
+        id: document.id,
+        filename: document.filename,
+        original_filename: document.original_filename,
+        mime_type: document.mime_type,
+        file_size: document.file_size,
+        file_hash: document.file_hash,
+        tags: document.tags,
+        label_ids,
+        ocr_status: document.ocr_status,
+        has_ocr_text: document.ocr_text.is_some(),
+        created_at: document.created_at,
+        updated_at: document.updated_at,
+    }
+}
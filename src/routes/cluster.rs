@@ -0,0 +1,100 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{delete, get},
+    Router,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{auth::AuthUser, models::WorkerNode, routes::queue::require_admin, AppState};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/nodes", get(list_worker_nodes))
+        .route("/nodes/{id}", delete(remove_worker_node))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Processes sharing the database, with their roles, OCR load and the background tasks
+/// running on them (admin only)
+///
+/// Nodes online come first. A node is offline once it has not reported for a minute;
+/// nodes offline for a week are no longer listed.
+#[utoipa::path(
+    get,
+    path = "/api/cluster/nodes",
+    tag = "cluster",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Worker nodes", body = Vec<WorkerNode>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_worker_nodes(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<WorkerNode>>, StatusCode> {
+    require_admin(&auth_user)?;
+    let nodes = state
+        .db
+        .get_worker_nodes()
+        .await
+        .map_err(|e| internal_error("Failed to list worker nodes", e))?;
+
+    Ok(Json(nodes))
+}
+
+/// Remove an offline node from the list (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/cluster/nodes/{id}",
+    tag = "cluster",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Node ID")
+    ),
+    responses(
+        (status = 204, description = "Node removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Node not found"),
+        (status = 409, description = "The node is online"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn remove_worker_node(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+    if !state
+        .db
+        .delete_offline_worker_node(id)
+        .await
+        .map_err(|e| internal_error("Failed to remove worker node", e))?
+    {
+        let node = state
+            .db
+            .get_worker_node(id)
+            .await
+            .map_err(|e| internal_error("Failed to get worker node", e))?;
+        return Err(if node.is_some() { StatusCode::CONFLICT } else { StatusCode::NOT_FOUND });
+    }
+    info!("User {} removed worker node {}", auth_user.user.username, id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
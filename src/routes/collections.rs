@@ -0,0 +1,541 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{Json, Response},
+    routing::{delete, get, post, put},
+    Router,
+};
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        Collection, CollectionDetail, CollectionDocument, CollectionDocumentsRequest, CollectionListItem,
+        CreateCollectionRequest, MoveCollectionRequest, ReorderCollectionRequest, SharePermission,
+        UpdateCollectionRequest, User, UserRole,
+    },
+    services::collection_export_service::{CollectionExportService, MAX_EXPORT_DOCUMENTS},
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_collections).post(create_collection))
+        .route("/{id}", get(get_collection).put(update_collection).delete(delete_collection))
+        .route("/{id}/move", post(move_collection))
+        .route("/{id}/order", put(reorder_collection))
+        .route("/{id}/documents", post(add_collection_documents))
+        .route("/{id}/documents/{document_id}", delete(remove_collection_document))
+        .route("/{id}/export", get(export_collection))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// A collection the user has at least `required` access to, with that access: full for
+/// its owner and admins, otherwise what shares of it or an enclosing collection grant
+async fn load_collection(
+    state: &AppState,
+    user: &User,
+    id: Uuid,
+    required: SharePermission,
+) -> Result<(Collection, SharePermission), StatusCode> {
+    let collection = state
+        .db
+        .get_collection(id)
+        .await
+        .map_err(|e| internal_error("Failed to get collection", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let permission = if collection.user_id == user.id || user.role == UserRole::Admin {
+        SharePermission::Delete
+    } else {
+        state
+            .db
+            .get_collection_share_permission(id, user.id)
+            .await
+            .map_err(|e| internal_error("Failed to get collection permission", e))?
+            .ok_or(StatusCode::NOT_FOUND)?
+    };
+    if permission < required {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok((collection, permission))
+}
+
+async fn collection_detail(
+    state: &AppState,
+    user: &User,
+    collection: Collection,
+    permission: SharePermission,
+) -> Result<CollectionDetail, StatusCode> {
+    let documents = state
+        .db
+        .get_collection_documents(collection.id, user.id, user.role)
+        .await
+        .map_err(|e| internal_error("Failed to list collection documents", e))?;
+    let children = state
+        .db
+        .get_child_collections(collection.id)
+        .await
+        .map_err(|e| internal_error("Failed to list subcollections", e))?;
+
+    Ok(CollectionDetail { collection, permission, documents, children })
+}
+
+/// The user's collections and those shared with them, with the subcollections of
+/// shared ones. Nesting is given by `parent_id`; top-level collections come first.
+#[utoipa::path(
+    get,
+    path = "/api/collections",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Collections the user can open", body = Vec<CollectionListItem>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_collections(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<CollectionListItem>>, StatusCode> {
+    let collections = state
+        .db
+        .get_collections_for_user(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list collections", e))?;
+
+    Ok(Json(collections))
+}
+
+/// Create a collection, at the top level or inside one the user can edit. A
+/// subcollection belongs to the owner of its parent.
+#[utoipa::path(
+    post,
+    path = "/api/collections",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateCollectionRequest,
+    responses(
+        (status = 201, description = "Collection created", body = Collection),
+        (status = 400, description = "Empty or too long name, or too long description"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Parent collection not found or not editable"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_collection(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateCollectionRequest>,
+) -> Result<(StatusCode, Json<Collection>), StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected collection: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let owner_id = match request.parent_id {
+        Some(parent_id) => {
+            let (parent, _) = load_collection(&state, &auth_user.user, parent_id, SharePermission::Edit).await?;
+            parent.user_id
+        }
+        None => auth_user.user.id,
+    };
+
+    let collection = state
+        .db
+        .create_collection(owner_id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to create collection", e))?;
+    info!("User {} created collection {}", auth_user.user.id, collection.id);
+
+    Ok((StatusCode::CREATED, Json(collection)))
+}
+
+/// A collection with its documents the user can see, in order, and its subcollections
+#[utoipa::path(
+    get,
+    path = "/api/collections/{id}",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID")
+    ),
+    responses(
+        (status = 200, description = "The collection", body = CollectionDetail),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_collection(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CollectionDetail>, StatusCode> {
+    let (collection, permission) = load_collection(&state, &auth_user.user, id, SharePermission::View).await?;
+
+    Ok(Json(collection_detail(&state, &auth_user.user, collection, permission).await?))
+}
+
+/// Rename a collection or change its description
+#[utoipa::path(
+    put,
+    path = "/api/collections/{id}",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID")
+    ),
+    request_body = UpdateCollectionRequest,
+    responses(
+        (status = 200, description = "Collection updated", body = Collection),
+        (status = 400, description = "Empty or too long name, or too long description"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection not found or not editable"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_collection(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateCollectionRequest>,
+) -> Result<Json<Collection>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected collection change: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    load_collection(&state, &auth_user.user, id, SharePermission::Edit).await?;
+
+    let collection = state
+        .db
+        .update_collection(id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to update collection", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(collection))
+}
+
+/// Delete a collection and its subcollections. Their documents are not deleted.
+#[utoipa::path(
+    delete,
+    path = "/api/collections/{id}",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID")
+    ),
+    responses(
+        (status = 204, description = "Collection deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection not found or not deletable"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_collection(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    load_collection(&state, &auth_user.user, id, SharePermission::Delete).await?;
+
+    let deleted = state
+        .db
+        .delete_collection(id)
+        .await
+        .map_err(|e| internal_error("Failed to delete collection", e))?;
+
+    if deleted {
+        info!("User {} deleted collection {}", auth_user.user.id, id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Move a collection into another collection of the same owner, after its
+/// subcollections, or to the top level. Moving to the top level takes delete access,
+/// as it can take the collection out of a shared parent.
+#[utoipa::path(
+    post,
+    path = "/api/collections/{id}/move",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID")
+    ),
+    request_body = MoveCollectionRequest,
+    responses(
+        (status = 200, description = "Collection moved", body = Collection),
+        (status = 400, description = "The new parent belongs to another user"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection or new parent not found or not editable"),
+        (status = 409, description = "The new parent is the collection itself or inside it"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn move_collection(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<MoveCollectionRequest>,
+) -> Result<Json<Collection>, StatusCode> {
+    match request.parent_id {
+        Some(parent_id) => {
+            let (collection, _) = load_collection(&state, &auth_user.user, id, SharePermission::Edit).await?;
+            let (parent, _) = load_collection(&state, &auth_user.user, parent_id, SharePermission::Edit).await?;
+            if parent.user_id != collection.user_id {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+        None => {
+            load_collection(&state, &auth_user.user, id, SharePermission::Delete).await?;
+        }
+    }
+
+    let moved = state
+        .db
+        .move_collection(id, request.parent_id)
+        .await
+        .map_err(|e| internal_error("Failed to move collection", e))?;
+    if !moved {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let collection = state
+        .db
+        .get_collection(id)
+        .await
+        .map_err(|e| internal_error("Failed to get collection", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(collection))
+}
+
+/// Put documents and subcollections in a new order. The listed ones come first, in
+/// the given order; the others follow in their current order.
+#[utoipa::path(
+    put,
+    path = "/api/collections/{id}/order",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID")
+    ),
+    request_body = ReorderCollectionRequest,
+    responses(
+        (status = 200, description = "The collection in its new order", body = CollectionDetail),
+        (status = 400, description = "An ID listed twice, or too many IDs"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection not found or not editable"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn reorder_collection(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ReorderCollectionRequest>,
+) -> Result<Json<CollectionDetail>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected collection order: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let (collection, permission) = load_collection(&state, &auth_user.user, id, SharePermission::Edit).await?;
+
+    state
+        .db
+        .reorder_collection(id, &request.document_ids, &request.collection_ids)
+        .await
+        .map_err(|e| internal_error("Failed to reorder collection", e))?;
+
+    Ok(Json(collection_detail(&state, &auth_user.user, collection, permission).await?))
+}
+
+/// Append documents to a collection, in the given order. Documents the user cannot
+/// see and documents already in the collection are left out.
+#[utoipa::path(
+    post,
+    path = "/api/collections/{id}/documents",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID")
+    ),
+    request_body = CollectionDocumentsRequest,
+    responses(
+        (status = 200, description = "The collection's documents the user can see, in order", body = Vec<CollectionDocument>),
+        (status = 400, description = "No documents, an ID listed twice, or too many IDs"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection not found or not editable"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn add_collection_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CollectionDocumentsRequest>,
+) -> Result<Json<Vec<CollectionDocument>>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected collection documents: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    load_collection(&state, &auth_user.user, id, SharePermission::Edit).await?;
+
+    let added = state
+        .db
+        .add_collection_documents(id, &request.document_ids, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| internal_error("Failed to add documents to collection", e))?;
+    info!("User {} added {} document(s) to collection {}", auth_user.user.id, added, id);
+
+    let documents = state
+        .db
+        .get_collection_documents(id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| internal_error("Failed to list collection documents", e))?;
+    Ok(Json(documents))
+}
+
+/// Take a document out of a collection. The document itself stays.
+#[utoipa::path(
+    delete,
+    path = "/api/collections/{id}/documents/{document_id}",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID"),
+        ("document_id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 204, description = "Document removed from the collection"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection not found or not editable, or document not in it"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn remove_collection_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, document_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    load_collection(&state, &auth_user.user, id, SharePermission::Edit).await?;
+
+    let removed = state
+        .db
+        .remove_collection_document(id, document_id)
+        .await
+        .map_err(|e| internal_error("Failed to remove document from collection", e))?;
+
+    if removed {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Download a collection as a single PDF: its documents in order, then those of each
+/// subcollection in turn. Only documents the user can see are included. PDFs and
+/// office documents are merged as they are; images get a page each when OCR is
+/// available. Other documents are skipped and counted in `X-Skipped-Documents`.
+#[utoipa::path(
+    get,
+    path = "/api/collections/{id}/export",
+    tag = "collections",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID")
+    ),
+    responses(
+        (status = 200, description = "The merged PDF", content_type = "application/pdf"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection not found"),
+        (status = 413, description = "More documents than a single export may contain"),
+        (status = 422, description = "None of the documents can be rendered as PDF"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn export_collection(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response, StatusCode> {
+    let (collection, _) = load_collection(&state, &auth_user.user, id, SharePermission::View).await?;
+
+    let document_ids = state
+        .db
+        .get_collection_tree_document_ids(id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| internal_error("Failed to list collection documents", e))?;
+    if document_ids.is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    if document_ids.len() > MAX_EXPORT_DOCUMENTS {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    let language = state
+        .db
+        .get_user_settings(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to get user settings", e))?
+        .map(|settings| settings.ocr_language)
+        .unwrap_or_else(|| "eng".to_string());
+
+    let service = CollectionExportService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let export = service
+        .export(&document_ids, &language)
+        .await
+        .map_err(|e| internal_error("Failed to export collection", e))?
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    info!(
+        "User {} exported collection {} ({} documents, {} skipped)",
+        auth_user.user.id,
+        id,
+        export.included,
+        export.skipped.len()
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", collection.export_filename()),
+        )
+        .header(header::CACHE_CONTROL, "no-store")
+        .header("X-Exported-Documents", export.included.to_string())
+        .header("X-Skipped-Documents", export.skipped.len().to_string())
+        .body(Body::from(export.pdf))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
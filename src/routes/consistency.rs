@@ -0,0 +1,145 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::{
+    auth::AuthUser,
+    models::{ConsistencyCheckRequest, ConsistencyRepairRequest, ConsistencyRepairResult, ConsistencyReport},
+    routes::queue::require_admin,
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        consistency_service::{ConsistencyService, RepairError},
+    },
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(get_consistency_report).post(run_consistency_check))
+        .route("/repair", post(repair_consistency_issue))
+}
+
+/// Report of the most recent consistency check
+#[utoipa::path(
+    get,
+    path = "/api/consistency",
+    tag = "consistency",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Most recent consistency check report", body = ConsistencyReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "No consistency check has run since startup")
+    )
+)]
+async fn get_consistency_report(auth_user: AuthUser) -> Result<Json<ConsistencyReport>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    ConsistencyService::last_report()
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Check documents, stored files, graph edges and queue entries against each other
+#[utoipa::path(
+    post,
+    path = "/api/consistency",
+    tag = "consistency",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = ConsistencyCheckRequest,
+    responses(
+        (status = 200, description = "Consistency check report", body = ConsistencyReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn run_consistency_check(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<ConsistencyCheckRequest>,
+) -> Result<Json<ConsistencyReport>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let service = ConsistencyService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let report = service.run(request.repair, request.verify_hashes).await.map_err(|e| {
+        error!("Consistency check failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(report))
+}
+
+/// Fix one issue of a consistency report: re-ingest an orphaned file or a document
+/// whose file changed, mark a document as missing its file, or delete an orphaned file
+#[utoipa::path(
+    post,
+    path = "/api/consistency/repair",
+    tag = "consistency",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = ConsistencyRepairRequest,
+    responses(
+        (status = 200, description = "Issue repaired", body = ConsistencyRepairResult),
+        (status = 400, description = "The action is missing its document or path, or the path is not in document storage"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Document or file not found"),
+        (status = 409, description = "The issue no longer holds"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn repair_consistency_issue(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<ConsistencyRepairRequest>,
+) -> Result<Json<ConsistencyRepairResult>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let service = ConsistencyService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let result = service
+        .repair(&request, auth_user.user.id, &state.queue_service)
+        .await
+        .map_err(|e| match e {
+            RepairError::Invalid(reason) => {
+                warn!("Rejected consistency repair {:?}: {}", request.action, reason);
+                StatusCode::BAD_REQUEST
+            }
+            RepairError::NotFound => StatusCode::NOT_FOUND,
+            RepairError::Conflict(reason) => {
+                warn!("Rejected consistency repair {:?}: {}", request.action, reason);
+                StatusCode::CONFLICT
+            }
+            RepairError::Failed(e) => {
+                error!("Consistency repair {:?} failed: {}", request.action, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::CONSISTENCY_REPAIR, "document", result.document_id).details(serde_json::json!({
+            "action": result.action,
+            "path": result.path,
+        })),
+    )
+    .await;
+
+    info!("Admin {} repaired a consistency issue: {}", auth_user.user.id, result.detail);
+    Ok(Json(result))
+}
@@ -0,0 +1,346 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        validate_match_rule, AssignCorrespondentRequest, AssignCorrespondentResponse, Correspondent,
+        CorrespondentWithCount, CreateCorrespondentRequest, DocumentCorrespondent, MatchCorrespondentsResponse,
+        UpdateCorrespondentRequest,
+    },
+    services::correspondent_service::{normalize_name, CorrespondentService},
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_correspondents).post(create_correspondent))
+        .route("/assign", post(assign_correspondent))
+        .route("/match", post(match_correspondents))
+        .route(
+            "/{id}",
+            get(get_correspondent)
+                .put(update_correspondent)
+                .delete(delete_correspondent),
+        )
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+fn validate_rule(algorithm: &str, pattern: Option<&str>) -> Result<(), StatusCode> {
+    validate_match_rule(algorithm, pattern).map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+async fn ensure_name_free(state: &AppState, user_id: Uuid, name: &str, except: Option<Uuid>) -> Result<(), StatusCode> {
+    let taken = state
+        .db
+        .correspondent_name_taken(user_id, name, except)
+        .await
+        .map_err(|e| internal_error("Failed to check correspondent name", e))?;
+    if taken {
+        return Err(StatusCode::CONFLICT);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/correspondents",
+    tag = "correspondents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Correspondents of the current user with their document counts", body = Vec<CorrespondentWithCount>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_correspondents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<CorrespondentWithCount>>, StatusCode> {
+    let correspondents = state
+        .db
+        .get_correspondents_with_counts(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list correspondents", e))?;
+
+    Ok(Json(correspondents))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/correspondents",
+    tag = "correspondents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateCorrespondentRequest,
+    responses(
+        (status = 201, description = "Correspondent created", body = Correspondent),
+        (status = 400, description = "Invalid name, match algorithm or pattern"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "A correspondent with this name already exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_correspondent(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateCorrespondentRequest>,
+) -> Result<(StatusCode, Json<Correspondent>), StatusCode> {
+    let name = normalize_name(&request.name).ok_or(StatusCode::BAD_REQUEST)?;
+    validate_rule(request.match_algorithm.as_deref().unwrap_or("auto"), request.match_pattern.as_deref())?;
+    ensure_name_free(&state, auth_user.user.id, &name, None).await?;
+
+    let correspondent = state
+        .db
+        .create_correspondent(auth_user.user.id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to create correspondent", e))?;
+
+    Ok((StatusCode::CREATED, Json(correspondent)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/correspondents/{id}",
+    tag = "correspondents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Correspondent ID")
+    ),
+    responses(
+        (status = 200, description = "Correspondent", body = Correspondent),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Correspondent not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_correspondent(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Correspondent>, StatusCode> {
+    let correspondent = state
+        .db
+        .get_correspondent(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to get correspondent", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(correspondent))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/correspondents/{id}",
+    tag = "correspondents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Correspondent ID")
+    ),
+    request_body = UpdateCorrespondentRequest,
+    responses(
+        (status = 200, description = "Correspondent updated", body = Correspondent),
+        (status = 400, description = "Invalid name, match algorithm or pattern"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Correspondent not found"),
+        (status = 409, description = "A correspondent with this name already exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_correspondent(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateCorrespondentRequest>,
+) -> Result<Json<Correspondent>, StatusCode> {
+    let existing = state
+        .db
+        .get_correspondent(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to get correspondent", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Validate the rule as it will be after the update
+    validate_rule(
+        request.match_algorithm.as_deref().unwrap_or(&existing.match_algorithm),
+        request.match_pattern.as_deref().or(existing.match_pattern.as_deref()),
+    )?;
+
+    if let Some(name) = &request.name {
+        let name = normalize_name(name).ok_or(StatusCode::BAD_REQUEST)?;
+        ensure_name_free(&state, auth_user.user.id, &name, Some(id)).await?;
+    }
+
+    let correspondent = state
+        .db
+        .update_correspondent(id, auth_user.user.id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to update correspondent", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(correspondent))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/correspondents/{id}",
+    tag = "correspondents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Correspondent ID")
+    ),
+    responses(
+        (status = 204, description = "Correspondent deleted; its documents no longer have one"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Correspondent not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_correspondent(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_correspondent(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to delete correspondent", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Set or clear the correspondent of many documents at once
+#[utoipa::path(
+    post,
+    path = "/api/correspondents/assign",
+    tag = "correspondents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = AssignCorrespondentRequest,
+    responses(
+        (status = 200, description = "Number of documents updated", body = AssignCorrespondentResponse),
+        (status = 400, description = "No documents or more than 1000"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Correspondent not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn assign_correspondent(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<AssignCorrespondentRequest>,
+) -> Result<Json<AssignCorrespondentResponse>, StatusCode> {
+    if request.document_ids.is_empty() || request.document_ids.len() > 1000 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if let Some(correspondent_id) = request.correspondent_id {
+        state
+            .db
+            .get_correspondent(correspondent_id, auth_user.user.id)
+            .await
+            .map_err(|e| internal_error("Failed to get correspondent", e))?
+            .ok_or(StatusCode::NOT_FOUND)?;
+    }
+
+    let updated = state
+        .db
+        .assign_correspondent(auth_user.user.id, &request.document_ids, request.correspondent_id, "manual")
+        .await
+        .map_err(|e| internal_error("Failed to assign correspondent", e))?;
+
+    Ok(Json(AssignCorrespondentResponse { updated }))
+}
+
+/// Run the match rules and name detection over documents that have no correspondent
+#[utoipa::path(
+    post,
+    path = "/api/correspondents/match",
+    tag = "correspondents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Documents checked and assigned", body = MatchCorrespondentsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn match_correspondents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<MatchCorrespondentsResponse>, StatusCode> {
+    let (documents_checked, documents_assigned) = CorrespondentService::new(state.db.clone())
+        .match_existing(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to match correspondents", e))?;
+
+    Ok(Json(MatchCorrespondentsResponse { documents_checked, documents_assigned }))
+}
+
+/// The correspondent of a document
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/correspondent",
+    tag = "correspondents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The document's correspondent and how it was assigned", body = DocumentCorrespondent),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found or without correspondent"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_correspondent(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<DocumentCorrespondent>, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let correspondent = state
+        .db
+        .get_document_correspondent(document_id)
+        .await
+        .map_err(|e| internal_error("Failed to get document correspondent", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(correspondent))
+}
@@ -0,0 +1,71 @@
+use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    auth::AuthUser,
+    models::{ContentDestination, DataResidencyStatus, IngestionHookKind},
+    routes::queue::require_admin,
+    services::{
+        data_residency, external_search::ExternalSearchConfig, llm::llm_service::LLMService,
+        narration_service::NarrationService, translation_service::TranslationService,
+    },
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/", get(get_data_residency))
+}
+
+/// The data residency policy and, for each configured service that can receive
+/// document content, whether it is let through (admin only). Content sent and content
+/// kept in are recorded in the audit log as `content.transmit` and
+/// `content.transmit_blocked`.
+#[utoipa::path(
+    get,
+    path = "/api/data-residency",
+    tag = "data_residency",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Policy in force and the configured services", body = DataResidencyStatus),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_data_residency(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<DataResidencyStatus>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let mut destinations = Vec::new();
+    let llm = LLMService::new(state.db.get_pool().clone());
+    if llm.configured() {
+        destinations.push(data_residency::destination_status(ContentDestination::Llm, None, llm.api_url()));
+    }
+    if let Some(url) = TranslationService::api_url() {
+        destinations.push(data_residency::destination_status(ContentDestination::Translation, None, url));
+    }
+    if let Some(url) = NarrationService::api_url() {
+        destinations.push(data_residency::destination_status(ContentDestination::TextToSpeech, None, url));
+    }
+    if let Ok(Some(config)) = ExternalSearchConfig::from_env() {
+        destinations.push(data_residency::destination_status(ContentDestination::SearchEngine, None, &config.url));
+    }
+
+    let hooks = state.db.list_ingestion_hooks().await.map_err(|e| {
+        error!("Failed to list ingestion hooks: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    destinations.extend(
+        hooks
+            .iter()
+            .filter(|hook| hook.enabled && hook.kind == IngestionHookKind::Webhook)
+            .map(|hook| data_residency::destination_status(ContentDestination::IngestionHook, Some(&hook.name), &hook.target)),
+    );
+
+    Ok(Json(DataResidencyStatus { policy: data_residency::policy().clone(), destinations }))
+}
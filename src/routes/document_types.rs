@@ -0,0 +1,387 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        normalize_field_definitions, CreateDocumentTypeRequest, DocumentCustomFields, DocumentType,
+        SetDocumentCustomFieldsRequest, SharePermission, UpdateDocumentTypeRequest,
+    },
+    services::event_service::EventService,
+    AppState,
+};
+
+/// Longest document type name
+const MAX_NAME_LENGTH: usize = 200;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_document_types).post(create_document_type))
+        .route(
+            "/{id}",
+            get(get_document_type)
+                .put(update_document_type)
+                .delete(delete_document_type),
+        )
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+fn bad_request(message: String) -> StatusCode {
+    warn!("Rejected document type request: {}", message);
+    StatusCode::BAD_REQUEST
+}
+
+fn normalize_type_name(name: &str) -> Result<String, StatusCode> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(name.to_string())
+}
+
+async fn ensure_name_free(state: &AppState, user_id: Uuid, name: &str, except: Option<Uuid>) -> Result<(), StatusCode> {
+    let taken = state
+        .db
+        .document_type_name_taken(user_id, name, except)
+        .await
+        .map_err(|e| internal_error("Failed to check document type name", e))?;
+    if taken {
+        return Err(StatusCode::CONFLICT);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/document-types",
+    tag = "document_types",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Document types of the current user", body = Vec<DocumentType>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_document_types(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<DocumentType>>, StatusCode> {
+    let document_types = state
+        .db
+        .get_document_types(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list document types", e))?;
+
+    Ok(Json(document_types))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/document-types",
+    tag = "document_types",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateDocumentTypeRequest,
+    responses(
+        (status = 201, description = "Document type created", body = DocumentType),
+        (status = 400, description = "Invalid name or field definitions"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "A document type with this name already exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_document_type(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateDocumentTypeRequest>,
+) -> Result<(StatusCode, Json<DocumentType>), StatusCode> {
+    let name = normalize_type_name(&request.name)?;
+    let fields = normalize_field_definitions(&request.fields).map_err(bad_request)?;
+    ensure_name_free(&state, auth_user.user.id, &name, None).await?;
+
+    let document_type = state
+        .db
+        .create_document_type(auth_user.user.id, &name, &fields)
+        .await
+        .map_err(|e| internal_error("Failed to create document type", e))?;
+
+    Ok((StatusCode::CREATED, Json(document_type)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/document-types/{id}",
+    tag = "document_types",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document type ID")
+    ),
+    responses(
+        (status = 200, description = "Document type", body = DocumentType),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document type not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_type(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DocumentType>, StatusCode> {
+    let document_type = state
+        .db
+        .get_document_type(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to get document type", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(document_type))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/document-types/{id}",
+    tag = "document_types",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document type ID")
+    ),
+    request_body = UpdateDocumentTypeRequest,
+    responses(
+        (status = 200, description = "Document type updated", body = DocumentType),
+        (status = 400, description = "Invalid name or field definitions"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document type not found"),
+        (status = 409, description = "A document type with this name already exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_document_type(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateDocumentTypeRequest>,
+) -> Result<Json<DocumentType>, StatusCode> {
+    let name = match &request.name {
+        Some(name) => {
+            let name = normalize_type_name(name)?;
+            ensure_name_free(&state, auth_user.user.id, &name, Some(id)).await?;
+            Some(name)
+        }
+        None => None,
+    };
+    let fields = match &request.fields {
+        Some(fields) => Some(normalize_field_definitions(fields).map_err(bad_request)?),
+        None => None,
+    };
+
+    let document_type = state
+        .db
+        .update_document_type(id, auth_user.user.id, name.as_deref(), fields.as_deref())
+        .await
+        .map_err(|e| internal_error("Failed to update document type", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(document_type))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/document-types/{id}",
+    tag = "document_types",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document type ID")
+    ),
+    responses(
+        (status = 204, description = "Document type deleted along with its documents' custom field values"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document type not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_document_type(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_document_type(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to delete document type", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// The document's type and custom field values
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/custom-fields",
+    tag = "document_types",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The document's type and custom field values", body = DocumentCustomFields),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found or without a type"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_custom_fields(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<DocumentCustomFields>, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let custom_fields = state
+        .db
+        .get_document_custom_fields(document_id)
+        .await
+        .map_err(|e| internal_error("Failed to get document custom fields", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(custom_fields))
+}
+
+/// Set the document's type and custom field values, validated against the type's fields
+#[utoipa::path(
+    put,
+    path = "/api/documents/{id}/custom-fields",
+    tag = "document_types",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    request_body = SetDocumentCustomFieldsRequest,
+    responses(
+        (status = 200, description = "The document's type and normalized custom field values", body = DocumentCustomFields),
+        (status = 400, description = "Unknown field, missing required field or value of the wrong type"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or document type not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn set_document_custom_fields(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Json(request): Json<SetDocumentCustomFieldsRequest>,
+) -> Result<Json<DocumentCustomFields>, StatusCode> {
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Edit)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Types come from the document owner, who may have shared the document
+    let document_type = state
+        .db
+        .get_document_type(request.document_type_id, document.user_id)
+        .await
+        .map_err(|e| internal_error("Failed to get document type", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let fields = document_type.validate_values(&request.fields).map_err(bad_request)?;
+
+    state
+        .db
+        .set_document_custom_fields(document_id, document_type.id, &fields)
+        .await
+        .map_err(|e| internal_error("Failed to set document custom fields", e))?;
+    EventService::new(state.db.clone())
+        .publish_document_updated(&document, &["custom_fields"])
+        .await;
+
+    let custom_fields = state
+        .db
+        .get_document_custom_fields(document_id)
+        .await
+        .map_err(|e| internal_error("Failed to get document custom fields", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(custom_fields))
+}
+
+/// Remove the document's type and custom field values
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}/custom-fields",
+    tag = "document_types",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 204, description = "Type and custom field values removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found or without a type"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn clear_document_custom_fields(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Edit)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let cleared = state
+        .db
+        .clear_document_custom_fields(document_id)
+        .await
+        .map_err(|e| internal_error("Failed to clear document custom fields", e))?;
+
+    if cleared {
+        EventService::new(state.db.clone())
+            .publish_document_updated(&document, &["custom_fields"])
+            .await;
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
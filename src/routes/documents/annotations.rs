@@ -0,0 +1,510 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Path, Query, State, WebSocketUpgrade,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{Json, Response},
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        validate_comment_body, AnnotationChange, AnnotationComment, AnnotationListQuery, AnnotationResponse,
+        CreateAnnotationCommentRequest, CreateAnnotationRequest, Document, DocumentAnnotation, SharePermission,
+        UpdateAnnotationCommentRequest, UpdateAnnotationRequest, User,
+    },
+    routes::sources::sync::extract_websocket_token_and_protocol,
+    services::annotation_service::{self, may_modify},
+    AppState,
+};
+
+/// How often an open annotation socket is pinged and re-checks that its user may still
+/// see the document
+const SOCKET_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// The document, when the user owns it or it is shared with them. Anyone who may view
+/// a document may annotate and discuss it.
+async fn load_document(state: &AppState, user: &User, document_id: Uuid) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, user.id, user.role, SharePermission::View)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn load_annotation(state: &AppState, document_id: Uuid, annotation_id: Uuid) -> Result<DocumentAnnotation, StatusCode> {
+    state
+        .db
+        .get_annotation(document_id, annotation_id)
+        .await
+        .map_err(|e| internal_error("Failed to get annotation", e))?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn load_comment(state: &AppState, annotation_id: Uuid, comment_id: Uuid) -> Result<AnnotationComment, StatusCode> {
+    state
+        .db
+        .get_annotation_comment(annotation_id, comment_id)
+        .await
+        .map_err(|e| internal_error("Failed to get comment", e))?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/annotations",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        AnnotationListQuery
+    ),
+    responses(
+        (status = 200, description = "Annotations in page order with their comment threads", body = Vec<AnnotationResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_document_annotations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Query(query): Query<AnnotationListQuery>,
+) -> Result<Json<Vec<AnnotationResponse>>, StatusCode> {
+    load_document(&state, &auth_user.user, document_id).await?;
+
+    let annotations = state
+        .db
+        .get_document_annotations(document_id, query.page)
+        .await
+        .map_err(|e| internal_error("Failed to list annotations", e))?;
+    let annotations = annotation_service::with_comments(&state.db, annotations)
+        .await
+        .map_err(|e| internal_error("Failed to load annotation comments", e))?;
+
+    Ok(Json(annotations))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/annotations",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    request_body = CreateAnnotationRequest,
+    responses(
+        (status = 201, description = "Annotation created", body = DocumentAnnotation),
+        (status = 400, description = "Invalid position, color or text"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_document_annotation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Json(request): Json<CreateAnnotationRequest>,
+) -> Result<(StatusCode, Json<DocumentAnnotation>), StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected annotation: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    load_document(&state, &auth_user.user, document_id).await?;
+
+    let annotation = state
+        .db
+        .create_annotation(document_id, auth_user.user.id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to create annotation", e))?;
+    info!("User {} annotated page {} of document {}", auth_user.user.id, annotation.page, document_id);
+
+    annotation_service::publish(document_id, AnnotationChange::AnnotationCreated { annotation: annotation.clone() });
+    Ok((StatusCode::CREATED, Json(annotation)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/documents/{id}/annotations/{annotation_id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        ("annotation_id" = Uuid, Path, description = "Annotation ID")
+    ),
+    request_body = UpdateAnnotationRequest,
+    responses(
+        (status = 200, description = "Annotation updated", body = DocumentAnnotation),
+        (status = 400, description = "Invalid position, color or text"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only the author, the document owner or an admin may change it"),
+        (status = 404, description = "Document or annotation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_document_annotation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((document_id, annotation_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<UpdateAnnotationRequest>,
+) -> Result<Json<DocumentAnnotation>, StatusCode> {
+    let document = load_document(&state, &auth_user.user, document_id).await?;
+    let current = load_annotation(&state, document_id, annotation_id).await?;
+    if !may_modify(&auth_user.user, &document, current.user_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if let Err(reason) = request.validate(&current) {
+        debug!("Rejected annotation update: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let annotation = state
+        .db
+        .update_annotation(document_id, annotation_id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to update annotation", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    annotation_service::publish(document_id, AnnotationChange::AnnotationUpdated { annotation: annotation.clone() });
+    Ok(Json(annotation))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}/annotations/{annotation_id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        ("annotation_id" = Uuid, Path, description = "Annotation ID")
+    ),
+    responses(
+        (status = 204, description = "Annotation and its comments deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only the author, the document owner or an admin may delete it"),
+        (status = 404, description = "Document or annotation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_document_annotation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((document_id, annotation_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    let document = load_document(&state, &auth_user.user, document_id).await?;
+    let annotation = load_annotation(&state, document_id, annotation_id).await?;
+    if !may_modify(&auth_user.user, &document, annotation.user_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let deleted = state
+        .db
+        .delete_annotation(document_id, annotation_id)
+        .await
+        .map_err(|e| internal_error("Failed to delete annotation", e))?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    annotation_service::publish(document_id, AnnotationChange::AnnotationDeleted { annotation_id });
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/annotations/{annotation_id}/comments",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        ("annotation_id" = Uuid, Path, description = "Annotation ID")
+    ),
+    request_body = CreateAnnotationCommentRequest,
+    responses(
+        (status = 201, description = "Comment added to the thread", body = AnnotationComment),
+        (status = 400, description = "Empty or too long body, or the replied-to comment is not in this thread"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or annotation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_annotation_comment(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((document_id, annotation_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<CreateAnnotationCommentRequest>,
+) -> Result<(StatusCode, Json<AnnotationComment>), StatusCode> {
+    if let Err(reason) = validate_comment_body(&request.body) {
+        debug!("Rejected annotation comment: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    load_document(&state, &auth_user.user, document_id).await?;
+    load_annotation(&state, document_id, annotation_id).await?;
+    if let Some(parent_id) = request.parent_id {
+        load_comment(&state, annotation_id, parent_id)
+            .await
+            .map_err(|status| if status == StatusCode::NOT_FOUND { StatusCode::BAD_REQUEST } else { status })?;
+    }
+
+    let comment = state
+        .db
+        .create_annotation_comment(annotation_id, request.parent_id, auth_user.user.id, &request.body)
+        .await
+        .map_err(|e| internal_error("Failed to create comment", e))?;
+
+    annotation_service::publish(document_id, AnnotationChange::CommentCreated { comment: comment.clone() });
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/documents/{id}/annotations/{annotation_id}/comments/{comment_id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        ("annotation_id" = Uuid, Path, description = "Annotation ID"),
+        ("comment_id" = Uuid, Path, description = "Comment ID")
+    ),
+    request_body = UpdateAnnotationCommentRequest,
+    responses(
+        (status = 200, description = "Comment edited", body = AnnotationComment),
+        (status = 400, description = "Empty or too long body"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only its author may edit a comment"),
+        (status = 404, description = "Document, annotation or comment not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_annotation_comment(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((document_id, annotation_id, comment_id)): Path<(Uuid, Uuid, Uuid)>,
+    Json(request): Json<UpdateAnnotationCommentRequest>,
+) -> Result<Json<AnnotationComment>, StatusCode> {
+    if let Err(reason) = validate_comment_body(&request.body) {
+        debug!("Rejected annotation comment: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    load_document(&state, &auth_user.user, document_id).await?;
+    load_annotation(&state, document_id, annotation_id).await?;
+    // Nobody puts words in someone else's mouth, not even the document owner
+    let current = load_comment(&state, annotation_id, comment_id).await?;
+    if current.user_id != Some(auth_user.user.id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let comment = state
+        .db
+        .update_annotation_comment(annotation_id, comment_id, &request.body)
+        .await
+        .map_err(|e| internal_error("Failed to update comment", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    annotation_service::publish(document_id, AnnotationChange::CommentUpdated { comment: comment.clone() });
+    Ok(Json(comment))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}/annotations/{annotation_id}/comments/{comment_id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        ("annotation_id" = Uuid, Path, description = "Annotation ID"),
+        ("comment_id" = Uuid, Path, description = "Comment ID")
+    ),
+    responses(
+        (status = 204, description = "Comment and its replies deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only the author, the document owner or an admin may delete it"),
+        (status = 404, description = "Document, annotation or comment not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_annotation_comment(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((document_id, annotation_id, comment_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    let document = load_document(&state, &auth_user.user, document_id).await?;
+    load_annotation(&state, document_id, annotation_id).await?;
+    let comment = load_comment(&state, annotation_id, comment_id).await?;
+    if !may_modify(&auth_user.user, &document, comment.user_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let deleted = state
+        .db
+        .delete_annotation_comment(annotation_id, comment_id)
+        .await
+        .map_err(|e| internal_error("Failed to delete comment", e))?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    annotation_service::publish(document_id, AnnotationChange::CommentDeleted { annotation_id, comment_id });
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// WebSocket stream of changes to a document's annotations and comments
+///
+/// Every message is a JSON object with a `type` and `data`:
+/// - `connection_confirmed`: the stream is open
+/// - `annotation_change`: an annotation or comment was created, updated or deleted;
+///   `data.change` says which
+/// - `resync_required`: changes were missed; reload the annotations
+/// - `error`: the stream is closing, e.g. because access to the document was revoked
+///
+/// Authentication works like the event stream, with the JWT in the
+/// `Sec-WebSocket-Protocol` header.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/annotations/ws",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 101, description = "WebSocket connection established - will stream annotation changes"),
+        (status = 401, description = "Unauthorized - invalid or missing authentication token"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error during WebSocket upgrade")
+    )
+)]
+pub async fn annotation_changes_websocket(
+    ws: WebSocketUpgrade,
+    Path(document_id): Path<Uuid>,
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, StatusCode> {
+    let (token, auth_protocol) = extract_websocket_token_and_protocol(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let (user_id, session_id) = crate::auth::authenticate_jwt(&state, &token)
+        .await
+        .map_err(|response| response.status())?;
+    let user = state
+        .db
+        .get_user_by_id(user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    load_document(&state, &user, document_id).await?;
+
+    Ok(ws
+        .protocols([auth_protocol])
+        .on_upgrade(move |socket| stream_annotation_changes(socket, state, document_id, user_id, session_id)))
+}
+
+fn stream_message(kind: &str, data: serde_json::Value) -> Message {
+    Message::Text(serde_json::json!({ "type": kind, "data": data }).to_string().into())
+}
+
+/// Whether the user is still logged in and may still see the document
+async fn socket_access_allowed(state: &AppState, user_id: Uuid, session_id: Option<Uuid>, document_id: Uuid) -> bool {
+    if let Some(session_id) = session_id {
+        if !matches!(state.db.get_active_session(session_id).await, Ok(Some(_))) {
+            return false;
+        }
+    }
+    match state.db.get_user_by_id(user_id).await {
+        Ok(Some(user)) => load_document(state, &user, document_id).await.is_ok(),
+        _ => false,
+    }
+}
+
+async fn stream_annotation_changes(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    document_id: Uuid,
+    user_id: Uuid,
+    session_id: Option<Uuid>,
+) {
+    let mut changes = annotation_service::subscribe();
+    let confirmation = stream_message(
+        "connection_confirmed",
+        serde_json::json!({ "document_id": document_id, "timestamp": chrono::Utc::now().timestamp() }),
+    );
+    if socket.send(confirmation).await.is_err() {
+        return;
+    }
+
+    let mut checks = tokio::time::interval(SOCKET_CHECK_INTERVAL);
+    checks.tick().await;
+
+    loop {
+        tokio::select! {
+            received = changes.recv() => match received {
+                Ok(change) => {
+                    if change.document_id != document_id {
+                        continue;
+                    }
+                    if socket.send(stream_message("annotation_change", serde_json::json!(change.change))).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Annotation stream of document {} fell {} changes behind", document_id, missed);
+                    let resync = stream_message("resync_required", serde_json::json!({ "reason": "lagged" }));
+                    if socket.send(resync).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = checks.tick() => {
+                if !socket_access_allowed(&state, user_id, session_id, document_id).await {
+                    info!("Closing annotation stream of document {}: user {} lost access", document_id, user_id);
+                    let revoked = stream_message(
+                        "error",
+                        serde_json::json!({
+                            "message": "Access to this document was revoked",
+                            "error_type": "access_revoked",
+                        }),
+                    );
+                    let _ = socket.send(revoked).await;
+                    break;
+                }
+                if socket.send(Message::Ping(vec![].into())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
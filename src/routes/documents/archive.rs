@@ -0,0 +1,231 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{Json, Response},
+};
+use bytes::Bytes;
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{SearchMode, SharePermission, MAX_BULK_DOCUMENTS},
+    services::audit_service::{self, document_details, AuditEvent, ClientInfo},
+    utils::{
+        search_query,
+        zip_stream::{EntryNames, ZipStreamWriter},
+    },
+    AppState,
+};
+use super::types::DownloadZipRequest;
+
+/// Archive chunks buffered ahead of a slow client
+const ARCHIVE_BUFFER_CHUNKS: usize = 16;
+/// Lists the documents left out of an archive, when there are any
+const ERRORS_ENTRY: &str = "readur-download-errors.txt";
+
+type ArchiveSender = mpsc::Sender<std::io::Result<Bytes>>;
+
+/// Download documents as a ZIP archive
+///
+/// The archive is streamed while it is written, so it has no Content-Length.
+/// Documents that cannot be read are left out and listed in `readur-download-errors.txt`.
+#[utoipa::path(
+    post,
+    path = "/api/documents/download-zip",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = DownloadZipRequest,
+    responses(
+        (status = 200, description = "ZIP archive of the documents under their original filenames", content_type = "application/zip"),
+        (status = 400, description = "Neither or both of document_ids and filter, or too many documents"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "No documents matched the filter"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn download_documents_zip(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<DownloadZipRequest>,
+) -> Result<Response<Body>, StatusCode> {
+    if let Err(e) = request.validate() {
+        debug!("Rejected ZIP download: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let document_ids: Vec<Uuid> = match (&request.document_ids, &request.filter) {
+        (Some(document_ids), _) => {
+            let mut seen = std::collections::HashSet::new();
+            document_ids.iter().copied().filter(|id| seen.insert(*id)).collect()
+        }
+        (None, Some(filter)) => {
+            if matches!(filter.search_mode, None | Some(SearchMode::Simple))
+                && search_query::parse(&filter.query).is_err()
+            {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            let document_ids = state
+                .db
+                .search_document_ids(
+                    auth_user.user.id,
+                    auth_user.user.role,
+                    &filter.to_search_request(None, None),
+                    MAX_BULK_DOCUMENTS as i64 + 1,
+                )
+                .await
+                .map_err(|e| {
+                    error!("Failed to resolve documents to download: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            if document_ids.is_empty() {
+                return Err(StatusCode::NOT_FOUND);
+            }
+            document_ids
+        }
+        (None, None) => return Err(StatusCode::BAD_REQUEST),
+    };
+    if document_ids.len() > MAX_BULK_DOCUMENTS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    info!("User {} is downloading {} documents as a ZIP archive", auth_user.user.id, document_ids.len());
+
+    let (sender, receiver) = mpsc::channel(ARCHIVE_BUFFER_CHUNKS);
+    let user_id = auth_user.user.id;
+    spawn_guarded(format!("ZIP download for user {}", user_id), async move {
+        write_archive(state, auth_user, client, document_ids, request.include_ocr_text, sender).await;
+    });
+
+    let filename = format!("readur-documents-{}.zip", chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/zip")
+        .header("Content-Disposition", format!("attachment; filename=\"{}\"", filename))
+        .body(Body::from_stream(receiver))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Write the archive into `sender` until it is done or the client goes away
+async fn write_archive(
+    state: Arc<AppState>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    document_ids: Vec<Uuid>,
+    include_ocr_text: bool,
+    mut sender: ArchiveSender,
+) {
+    let mut writer = ZipStreamWriter::new();
+    let mut names = EntryNames::new();
+    let mut failures = Vec::new();
+
+    for document_id in document_ids {
+        let document = match state
+            .db
+            .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
+            .await
+        {
+            Ok(Some(document)) => document,
+            Ok(None) => {
+                failures.push(format!("{}: not found", document_id));
+                continue;
+            }
+            Err(e) => {
+                error!("Failed to get document {} for a ZIP download: {}", document_id, e);
+                failures.push(format!("{}: could not be loaded", document_id));
+                continue;
+            }
+        };
+
+        let mut file_stream = match state.file_service.open_stream(&document.file_path).await {
+            Ok(file_stream) => file_stream,
+            Err(e) => {
+                warn!("Failed to read document {} for a ZIP download: {}", document.id, e);
+                failures.push(format!("{} ({}): file could not be read", document.original_filename, document.id));
+                continue;
+            }
+        };
+
+        let name = names.unique(&document.original_filename);
+        if send(&mut sender, Ok(writer.start_entry(&name, document.updated_at))).await.is_err() {
+            return;
+        }
+        while let Some(chunk) = file_stream.chunks.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    if send(&mut sender, Ok(writer.write(chunk))).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    // The entry is closed over what was read, keeping the archive valid
+                    warn!("Reading document {} for a ZIP download failed: {}", document.id, e);
+                    failures.push(format!("{} ({}): file is incomplete, reading it failed", name, document.id));
+                    break;
+                }
+            }
+        }
+        if send(&mut sender, writer.finish_entry()).await.is_err() {
+            return;
+        }
+
+        audit_service::record(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::DOCUMENT_DOWNLOAD, "document", Some(document.id))
+                .details(serde_json::json!({ "bulk": "zip", "document": document_details(&document) })),
+        )
+        .await;
+
+        if include_ocr_text {
+            if let Some(text) = document.ocr_text.as_deref().filter(|text| !text.trim().is_empty()) {
+                let sidecar = names.unique(&format!("{}.txt", name));
+                let entry = writer.add_entry(&sidecar, document.updated_at, Bytes::from(text.to_string()));
+                if send(&mut sender, entry).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        let report = Bytes::from(failures.join("\n") + "\n");
+        let entry = writer.add_entry(&names.unique(ERRORS_ENTRY), chrono::Utc::now(), report);
+        if send(&mut sender, entry).await.is_err() {
+            return;
+        }
+    }
+
+    if send(&mut sender, writer.finish()).await.is_ok() {
+        debug!("ZIP download for user {} finished, {} documents left out", auth_user.user.id, failures.len());
+    }
+}
+
+/// Hand a piece of the archive to the response. Err when the client went away or
+/// the archive cannot continue.
+async fn send(sender: &mut ArchiveSender, piece: std::io::Result<Bytes>) -> Result<(), ()> {
+    let failed = piece.is_err();
+    if let Err(e) = &piece {
+        error!("ZIP download aborted: {}", e);
+    }
+    if sender.send(piece).await.is_err() {
+        debug!("ZIP download cancelled by the client");
+        return Err(());
+    }
+    if failed {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    auth::AuthUser,
+    models::{PageArtifact, PageArtifactSearchQuery},
+    services::page_artifact_service::PageArtifactService,
+    AppState,
+};
+
+/// List stamps, signatures and figures detected on a document's pages
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/artifacts",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Page artifacts of the document", body = Vec<PageArtifact>),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_artifacts(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<Vec<PageArtifact>>, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let artifacts = state
+        .db
+        .get_page_artifacts(document_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load page artifacts for document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(artifacts))
+}
+
+/// Run (or re-run) artifact detection on a document
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/artifacts/extract",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Detected page artifacts", body = Vec<PageArtifact>),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Document type cannot be rasterized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn extract_document_artifacts(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<Vec<PageArtifact>>, StatusCode> {
+    let document = state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !document.mime_type.starts_with("image/") && document.mime_type != "application/pdf" {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let service = PageArtifactService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let artifacts = service
+        .extract_for_document(&document)
+        .await
+        .map_err(|e| {
+            error!("Page artifact extraction failed for document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(artifacts))
+}
+
+/// Find stamped, signed or illustrated pages across the library
+#[utoipa::path(
+    get,
+    path = "/api/documents/artifacts/search",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(PageArtifactSearchQuery),
+    responses(
+        (status = 200, description = "Matching page artifacts", body = Vec<PageArtifact>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn search_page_artifacts(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<PageArtifactSearchQuery>,
+) -> Result<Json<Vec<PageArtifact>>, StatusCode> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let artifacts = state
+        .db
+        .search_page_artifacts(
+            auth_user.user.id,
+            auth_user.user.role,
+            query.kind,
+            query.q.as_deref(),
+            limit,
+            offset,
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to search page artifacts: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(artifacts))
+}
@@ -0,0 +1,50 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{auth::AuthUser, models::DocumentAttachment, AppState};
+
+/// List documents created from the attachments of an email
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/attachments",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID of the email")
+    ),
+    responses(
+        (status = 200, description = "Attachment documents in the order they appear in the email", body = Vec<DocumentAttachment>),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_attachments(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<Vec<DocumentAttachment>>, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let attachments = state.db.get_document_attachments(document_id).await.map_err(|e| {
+        error!("Failed to load attachments of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(attachments))
+}
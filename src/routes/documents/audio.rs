@@ -0,0 +1,228 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{Json, Response},
+};
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use super::crud::{file_part_response, open_file_part};
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{DocumentAudio, NarrateQuery, SharePermission},
+    services::narration_service::{NarrationService, MAX_NARRATION_CHARS, NARRATION_MIME_TYPE},
+    AppState,
+};
+
+fn narration_service(state: &AppState) -> NarrationService {
+    NarrationService::new(state.db.clone(), state.file_service.as_ref().clone())
+}
+
+async fn load_document(
+    state: &AppState,
+    auth_user: &AuthUser,
+    id: Uuid,
+    permission: SharePermission,
+) -> Result<crate::models::Document, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(id, auth_user.user.id, auth_user.user.role, permission)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn load_audio(state: &AppState, id: Uuid) -> Result<DocumentAudio, StatusCode> {
+    state
+        .db
+        .get_document_audio(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get narration of document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Narrate the document's text with the text-to-speech API in the background.
+/// Narrating again replaces the audio once the new one is done.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/audio",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        NarrateQuery
+    ),
+    responses(
+        (status = 202, description = "Narration queued", body = DocumentAudio),
+        (status = 400, description = "Invalid voice"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 409, description = "The document is already being narrated"),
+        (status = 422, description = "The document has no text, or more than can be narrated"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "No text-to-speech API is configured")
+    )
+)]
+pub async fn narrate_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(query): Query<NarrateQuery>,
+) -> Result<(StatusCode, Json<DocumentAudio>), StatusCode> {
+    let voice = query.voice().map_err(|e| {
+        debug!("Invalid narration request: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let document = load_document(&state, &auth_user, id, SharePermission::Edit).await?;
+
+    if !NarrationService::enabled() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let text = NarrationService::document_text(&document).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    let source_chars = text.chars().count();
+    if source_chars > MAX_NARRATION_CHARS {
+        debug!("Document {} has {} characters, too many to narrate", id, source_chars);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let audio = state
+        .db
+        .queue_document_audio(
+            id,
+            voice.unwrap_or(NarrationService::default_voice()),
+            NarrationService::model(),
+            source_chars as i32,
+            auth_user.user.id,
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to queue narration of document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    let service = narration_service(&state);
+    spawn_guarded(format!("narration of document {}", id), async move {
+        if let Err(e) = service.run(id).await {
+            error!("Narration of document {} stopped: {}", id, e);
+        }
+    });
+
+    info!("Queued narration of document {} with voice {}", id, audio.voice);
+    Ok((StatusCode::ACCEPTED, Json(audio)))
+}
+
+/// Play the document's narration. Supports `Range` requests for seeking.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/audio",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The narration as MP3", content_type = "audio/mpeg"),
+        (status = 206, description = "The requested part of the narration", content_type = "audio/mpeg"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found, or not narrated yet"),
+        (status = 416, description = "Range not satisfiable"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_audio(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    load_document(&state, &auth_user, id, SharePermission::View).await?;
+    let audio = load_audio(&state, id).await?;
+    let file_path = audio.file_path.as_deref().ok_or(StatusCode::NOT_FOUND)?;
+
+    let part = open_file_part(&state.file_service, file_path, &headers).await.map_err(|e| {
+        error!("Failed to open narration of document {}: {}", id, e);
+        StatusCode::NOT_FOUND
+    })?;
+    file_part_response(part, NARRATION_MIME_TYPE, Some("inline"))
+}
+
+/// Whether the document has been narrated, and how far a narration has come
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/audio/status",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The narration", body = DocumentAudio),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found, or never narrated"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_audio_status(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DocumentAudio>, StatusCode> {
+    load_document(&state, &auth_user, id, SharePermission::View).await?;
+    Ok(Json(load_audio(&state, id).await?))
+}
+
+/// Remove the document's narration and its audio
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}/audio",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 204, description = "Narration removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found, or never narrated"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_document_audio(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let document = load_document(&state, &auth_user, id, SharePermission::Edit).await?;
+
+    let audio = state
+        .db
+        .delete_document_audio(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete narration of document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    // A narration still being spoken removes its audio itself when done
+    narration_service(&state).delete_audio_file(document.user_id, &audio).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
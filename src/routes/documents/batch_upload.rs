@@ -0,0 +1,230 @@
+use axum::{
+    extract::{Multipart, State},
+    response::Json,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        BatchUploadFileResult, BatchUploadFileStatus, BatchUploadManifest, BatchUploadResponse,
+    },
+    services::audit_service::ClientInfo,
+    AppState,
+};
+use super::crud::{ingest_upload, remove_document_as, DocumentError, UploadedFile};
+
+const DEFAULT_BATCH_UPLOAD_MAX_SIZE_MB: usize = 1024;
+
+/// Largest body of a batch upload, from `BATCH_UPLOAD_MAX_SIZE_MB`
+pub fn batch_upload_max_size_bytes() -> usize {
+    std::env::var("BATCH_UPLOAD_MAX_SIZE_MB")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_BATCH_UPLOAD_MAX_SIZE_MB)
+        .saturating_mul(1024 * 1024)
+}
+
+/// Upload several files with a manifest of their labels, correspondents and dates
+///
+/// The multipart body has one `files` part per file and a `manifest` part with the
+/// JSON of `BatchUploadManifest`, which needs an entry for every file. Each file is
+/// ingested like a single upload and then given the metadata of its entry. In an
+/// atomic batch (the default) the new documents are removed again when any file fails.
+#[utoipa::path(
+    post,
+    path = "/api/documents/bulk-upload",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body(content = String, description = "`files` parts and a `manifest` JSON part", content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Result of every file; `committed` is false when an atomic batch was rolled back", body = BatchUploadResponse),
+        (status = 400, description = "Missing or invalid manifest, a manifest that does not match the files, or unknown label IDs"),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "The batch is larger than BATCH_UPLOAD_MAX_SIZE_MB"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn batch_upload_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    mut multipart: Multipart,
+) -> Result<Json<BatchUploadResponse>, DocumentError> {
+    let mut files = Vec::new();
+    let mut manifest = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| DocumentError::BadRequest(format!("Failed to get multipart field: {}", e)))?
+    {
+        match field.name().unwrap_or("") {
+            "manifest" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|_| DocumentError::BadRequest("Failed to read the manifest".to_string()))?;
+                let parsed: BatchUploadManifest = serde_json::from_str(&text)
+                    .map_err(|e| DocumentError::BadRequest(format!("Invalid manifest: {}", e)))?;
+                manifest = Some(parsed);
+            }
+            "files" | "files[]" | "file" => {
+                let filename = field
+                    .file_name()
+                    .ok_or_else(|| DocumentError::BadRequest("No filename provided in upload".to_string()))?
+                    .to_string();
+                let content_type = crate::ocr::email_parser::email_mime_type(&filename)
+                    .or(field.content_type())
+                    .unwrap_or("application/octet-stream")
+                    .to_string();
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| DocumentError::BadRequest(format!("Failed to read file data: {}", e)))?;
+                files.push(UploadedFile { filename, content_type, data: data.to_vec() });
+            }
+            _ => {}
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| DocumentError::BadRequest("No manifest found in upload".to_string()))?;
+    let filenames: Vec<String> = files.iter().map(|file| file.filename.clone()).collect();
+    manifest.validate(&filenames).map_err(DocumentError::BadRequest)?;
+
+    let label_ids: HashSet<Uuid> = manifest.files.iter().flat_map(|entry| entry.label_ids.iter().copied()).collect();
+    if !label_ids.is_empty() {
+        let label_ids: Vec<Uuid> = label_ids.into_iter().collect();
+        let usable = state
+            .db
+            .count_usable_labels(auth_user.user.id, &label_ids)
+            .await
+            .map_err(|e| DocumentError::InternalServerError(format!("Failed to check labels: {}", e)))?;
+        if usable as usize != label_ids.len() {
+            return Err(DocumentError::BadRequest("The manifest names labels that do not exist".to_string()));
+        }
+    }
+
+    let mut results = Vec::with_capacity(files.len());
+    for file in files {
+        let filename = file.filename.clone();
+        let entry = manifest.entry(&filename).cloned().unwrap_or_default();
+        let result = match ingest_upload(&state, &auth_user, &client, file, None, Vec::new()).await {
+            Ok(upload) if upload.status == "duplicate" => BatchUploadFileResult {
+                filename,
+                status: BatchUploadFileStatus::Duplicate,
+                document_id: Some(upload.id),
+                error: None,
+            },
+            Ok(upload) => match state.db.apply_batch_upload_entry(auth_user.user.id, upload.id, &entry).await {
+                Ok(()) => BatchUploadFileResult {
+                    filename,
+                    status: BatchUploadFileStatus::Created,
+                    document_id: Some(upload.id),
+                    error: None,
+                },
+                Err(e) => {
+                    warn!("Failed to apply the manifest entry of {} to document {}: {}", filename, upload.id, e);
+                    // The document exists without its metadata; remove it like any failed file
+                    discard_document(&state, &auth_user, upload.id).await;
+                    BatchUploadFileResult {
+                        filename,
+                        status: BatchUploadFileStatus::Failed,
+                        document_id: None,
+                        error: Some("Failed to apply the manifest entry".to_string()),
+                    }
+                }
+            },
+            Err(e) => BatchUploadFileResult {
+                filename,
+                status: BatchUploadFileStatus::Failed,
+                document_id: None,
+                error: Some(error_message(e)),
+            },
+        };
+        let failed = result.status == BatchUploadFileStatus::Failed;
+        results.push(result);
+        if failed && manifest.atomic {
+            break;
+        }
+    }
+
+    let any_failed = results.iter().any(|result| result.status == BatchUploadFileStatus::Failed);
+    let committed = !(manifest.atomic && any_failed);
+    if !committed {
+        for result in results.iter_mut().filter(|result| result.status == BatchUploadFileStatus::Created) {
+            if let Some(document_id) = result.document_id.take() {
+                discard_document(&state, &auth_user, document_id).await;
+            }
+            result.status = BatchUploadFileStatus::RolledBack;
+        }
+        // Files after the failure were never ingested
+        let processed: HashSet<String> = results.iter().map(|result| result.filename.clone()).collect();
+        for filename in filenames.iter().filter(|filename| !processed.contains(*filename)) {
+            results.push(BatchUploadFileResult {
+                filename: filename.clone(),
+                status: BatchUploadFileStatus::RolledBack,
+                document_id: None,
+                error: None,
+            });
+        }
+    }
+
+    let count = |status: BatchUploadFileStatus| results.iter().filter(|result| result.status == status).count();
+    let response = BatchUploadResponse {
+        atomic: manifest.atomic,
+        committed,
+        created: count(BatchUploadFileStatus::Created),
+        duplicates: count(BatchUploadFileStatus::Duplicate),
+        failed: count(BatchUploadFileStatus::Failed),
+        results,
+    };
+    info!(
+        "Batch upload by user {}: {} created, {} duplicates, {} failed{}",
+        auth_user.user.id,
+        response.created,
+        response.duplicates,
+        response.failed,
+        if committed { "" } else { ", rolled back" }
+    );
+
+    Ok(Json(response))
+}
+
+/// Remove a document created by the batch, with its files
+async fn discard_document(state: &AppState, auth_user: &AuthUser, document_id: Uuid) {
+    let removed = async {
+        match state.db.get_document_by_id(document_id, auth_user.user.id, auth_user.user.role).await? {
+            Some(document) => remove_document_as(state, &document, auth_user.user.id, auth_user.user.role).await,
+            None => Ok(false),
+        }
+    };
+    if let Err(e) = removed.await {
+        error!("Failed to remove document {} of a rolled back batch upload: {}", document_id, e);
+    }
+}
+
+fn error_message(error: DocumentError) -> String {
+    match error {
+        DocumentError::NotFound => "Document not found".to_string(),
+        DocumentError::BadRequest(message)
+        | DocumentError::Forbidden(message)
+        | DocumentError::Conflict(message)
+        | DocumentError::PayloadTooLarge(message)
+        | DocumentError::QuotaExceeded(message)
+        | DocumentError::InternalServerError(message)
+        | DocumentError::UploadTimeout(message)
+        | DocumentError::DatabaseConstraintViolation(message)
+        | DocumentError::OcrProcessingError(message)
+        | DocumentError::FileProcessingError(message)
+        | DocumentError::ConcurrentUploadError(message)
+        | DocumentError::MalwareDetected(message)
+        | DocumentError::UnsupportedMediaType(message)
+        | DocumentError::RejectedByHook(message) => message,
+    }
+}
@@ -1,13 +1,23 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
 };
+use std::collections::HashSet;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 use crate::{
     auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{
+        BulkOperation, BulkOperationKind, BulkOperationRequest, SearchMode, UserRole, MAX_BULK_DOCUMENTS,
+    },
+    services::audit_service::{self, document_details, AuditEvent, ClientInfo},
+    services::bulk_operation_service::BulkOperationService,
+    services::title_service::TitleService,
+    utils::search_query,
     AppState,
 };
 use super::types::{BulkDeleteRequest, DeleteLowConfidenceRequest, BulkDeleteResponse};
@@ -31,6 +41,7 @@ use super::types::{BulkDeleteRequest, DeleteLowConfidenceRequest, BulkDeleteResp
 pub async fn bulk_delete_documents(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Json(request): Json<BulkDeleteRequest>,
 ) -> Result<Json<BulkDeleteResponse>, StatusCode> {
     if request.document_ids.is_empty() {
@@ -83,6 +94,14 @@ pub async fn bulk_delete_documents(
 
     for document in documents_to_delete {
         if deleted_ids.contains(&document.id) {
+            audit_service::record(
+                &state.db,
+                Some(&auth_user.user),
+                &client,
+                AuditEvent::new(audit_service::DOCUMENT_DELETE, "document", Some(document.id))
+                    .details(serde_json::json!({ "bulk": "selection", "document": document_details(&document) })),
+            )
+            .await;
             match file_service.delete_document_files(&document).await {
                 Ok(_) => files_deleted += 1,
                 Err(e) => {
@@ -90,6 +109,9 @@ pub async fn bulk_delete_documents(
                     files_failed += 1;
                 }
             }
+            if let Err(e) = crate::services::webdav::write_back::queue_document_deletion(&state.db, &document).await {
+                warn!("Failed to queue WebDAV deletion for document {}: {}", document.id, e);
+            }
         }
     }
 
@@ -127,6 +149,7 @@ pub async fn bulk_delete_documents(
 pub async fn delete_low_confidence_documents(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Json(request): Json<DeleteLowConfidenceRequest>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     if request.max_confidence < 0.0 || request.max_confidence > 100.0 {
@@ -201,6 +224,14 @@ pub async fn delete_low_confidence_documents(
 
     for document in low_confidence_docs {
         if deleted_ids.contains(&document.id) {
+            audit_service::record(
+                &state.db,
+                Some(&auth_user.user),
+                &client,
+                AuditEvent::new(audit_service::DOCUMENT_DELETE, "document", Some(document.id))
+                    .details(serde_json::json!({ "bulk": "low_confidence", "document": document_details(&document) })),
+            )
+            .await;
             match file_service.delete_document_files(&document).await {
                 Ok(_) => files_deleted += 1,
                 Err(e) => {
@@ -243,6 +274,7 @@ pub async fn delete_low_confidence_documents(
 pub async fn delete_failed_ocr_documents(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     info!("Finding documents with failed OCR");
 
@@ -287,6 +319,14 @@ pub async fn delete_failed_ocr_documents(
 
     for document in failed_ocr_docs {
         if deleted_ids.contains(&document.id) {
+            audit_service::record(
+                &state.db,
+                Some(&auth_user.user),
+                &client,
+                AuditEvent::new(audit_service::DOCUMENT_DELETE, "document", Some(document.id))
+                    .details(serde_json::json!({ "bulk": "failed_ocr", "document": document_details(&document) })),
+            )
+            .await;
             match file_service.delete_document_files(&document).await {
                 Ok(_) => files_deleted += 1,
                 Err(e) => {
@@ -397,4 +437,194 @@ pub async fn get_cleanup_preview(
             "include_failed_ocr": include_failed
         }
     })))
-}
\ No newline at end of file
+}
+/// Bulk operations listed per request
+const BULK_OPERATION_LIST_LIMIT: i64 = 50;
+
+/// Start an operation on many documents, given by id or by a search, in the background
+#[utoipa::path(
+    post,
+    path = "/api/documents/bulk",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = BulkOperationRequest,
+    responses(
+        (status = 202, description = "Operation queued; follow it at /api/documents/bulk/{id}", body = BulkOperation),
+        (status = 400, description = "Invalid request, unknown labels or owner, or too many documents"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only admins can change the owner of documents"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_bulk_operation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<BulkOperationRequest>,
+) -> Result<(StatusCode, Json<BulkOperation>), StatusCode> {
+    if let Err(e) = request.validate() {
+        debug!("Rejected bulk operation: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to start bulk operation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    match request.operation {
+        BulkOperationKind::AddLabels | BulkOperationKind::RemoveLabels => {
+            let label_ids: HashSet<Uuid> = request.parameters.label_ids.iter().copied().collect();
+            let usable = state
+                .db
+                .count_usable_labels(auth_user.user.id, &request.parameters.label_ids)
+                .await
+                .map_err(db_error)?;
+            if usable as usize != label_ids.len() {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+        BulkOperationKind::ChangeOwner => {
+            if auth_user.user.role != UserRole::Admin {
+                return Err(StatusCode::FORBIDDEN);
+            }
+            let owner_id = request.parameters.owner_id.ok_or(StatusCode::BAD_REQUEST)?;
+            if state.db.get_user_by_id(owner_id).await.map_err(db_error)?.is_none() {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+        BulkOperationKind::Retitle => {
+            let llm_only = request.parameters.title_mode.as_deref() == Some("llm");
+            if llm_only && !TitleService::new(state.db.clone()).llm_available() {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+        _ => {}
+    }
+
+    let document_ids: Vec<Uuid> = match (&request.document_ids, &request.filter) {
+        (Some(document_ids), _) => {
+            let mut seen = HashSet::new();
+            document_ids.iter().copied().filter(|id| seen.insert(*id)).collect()
+        }
+        (None, Some(filter)) => {
+            if matches!(filter.search_mode, None | Some(SearchMode::Simple))
+                && search_query::parse(&filter.query).is_err()
+            {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            state
+                .db
+                .search_document_ids(
+                    auth_user.user.id,
+                    auth_user.user.role,
+                    &filter.to_search_request(None, None),
+                    MAX_BULK_DOCUMENTS as i64 + 1,
+                )
+                .await
+                .map_err(db_error)?
+        }
+        (None, None) => return Err(StatusCode::BAD_REQUEST),
+    };
+    if document_ids.len() > MAX_BULK_DOCUMENTS
+        || (request.operation == BulkOperationKind::Merge && document_ids.len() < 2)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let operation = state
+        .db
+        .create_bulk_operation(auth_user.user.id, request.operation, &request.parameters, &document_ids)
+        .await
+        .map_err(db_error)?;
+
+    info!(
+        "User {} started bulk operation {} ({}) on {} documents",
+        auth_user.user.id,
+        operation.id,
+        operation.operation,
+        operation.total_documents
+    );
+
+    let service = BulkOperationService::new(state.clone());
+    let operation_id = operation.id;
+    spawn_guarded(format!("bulk operation {}", operation_id), async move {
+        if let Err(e) = service.run(operation_id, client).await {
+            error!("Bulk operation {} stopped: {}", operation_id, e);
+        }
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(operation)))
+}
+
+/// The user's bulk operations, newest first
+#[utoipa::path(
+    get,
+    path = "/api/documents/bulk",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Recent bulk operations with their progress", body = Vec<BulkOperation>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_bulk_operations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<BulkOperation>>, StatusCode> {
+    let operations = state
+        .db
+        .list_bulk_operations(auth_user.user.id, BULK_OPERATION_LIST_LIMIT)
+        .await
+        .map_err(|e| {
+            error!("Failed to list bulk operations: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(operations))
+}
+
+/// Status and progress of a bulk operation
+#[utoipa::path(
+    get,
+    path = "/api/documents/bulk/{id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Bulk operation ID")
+    ),
+    responses(
+        (status = 200, description = "Bulk operation", body = BulkOperation),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Bulk operation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_bulk_operation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<BulkOperation>, StatusCode> {
+    let operation = state
+        .db
+        .get_bulk_operation(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get bulk operation {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if operation.user_id != auth_user.user.id && auth_user.user.role != UserRole::Admin {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(operation))
+}
@@ -0,0 +1,176 @@
+use axum::{
+    extract::{Multipart, State},
+    response::Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::{
+    auth::AuthUser,
+    services::audit_service::ClientInfo,
+    services::capture_service::{self, UnusablePhoto},
+    AppState,
+};
+use super::crud::{ingest_upload, DocumentError, UploadedFile};
+use super::types::{CaptureMetadata, DocumentUploadResponse};
+
+/// Key of a document's metadata the capture details are kept under
+pub const CAPTURE_METADATA_KEY: &str = "capture";
+
+/// Upload a document photographed with a phone
+///
+/// The multipart body has one `page` part per photo, in page order, and an optional
+/// `metadata` part with the JSON of `CaptureMetadata`. Each photo is cut to the page
+/// corners the app detected and straightened, turned upright and given more contrast;
+/// the pages are assembled into a searchable PDF that is ingested like any upload.
+#[utoipa::path(
+    post,
+    path = "/api/documents/capture",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body(content = String, description = "`page` photos in order, `metadata` JSON and optional `ocr_language`", content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Capture assembled and uploaded", body = DocumentUploadResponse),
+        (status = 400, description = "No photos, too many, a photo that cannot be read, or invalid metadata"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "The workspace named by X-Workspace-Id requires the editor role to upload"),
+        (status = 413, description = "The photos or the assembled PDF are too large, or it does not fit in the user's storage quota"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn upload_capture(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    mut multipart: Multipart,
+) -> Result<Json<DocumentUploadResponse>, DocumentError> {
+    let mut photos = Vec::new();
+    let mut metadata = CaptureMetadata::default();
+    let mut ocr_language: Option<String> = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| DocumentError::BadRequest(format!("Failed to get multipart field: {}", e)))?
+    {
+        match field.name().unwrap_or("") {
+            "page" | "pages" | "pages[]" => {
+                if photos.len() == capture_service::MAX_CAPTURE_PAGES {
+                    return Err(DocumentError::BadRequest(format!(
+                        "A capture may have at most {} pages",
+                        capture_service::MAX_CAPTURE_PAGES
+                    )));
+                }
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| DocumentError::BadRequest(format!("Failed to read page photo: {}", e)))?;
+                photos.push(data.to_vec());
+            }
+            "metadata" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|_| DocumentError::BadRequest("Failed to read metadata field".to_string()))?;
+                metadata = serde_json::from_str(&text)
+                    .map_err(|e| DocumentError::BadRequest(format!("Invalid capture metadata: {}", e)))?;
+            }
+            "ocr_language" => {
+                let language = field
+                    .text()
+                    .await
+                    .map_err(|_| DocumentError::BadRequest("Failed to read language field".to_string()))?;
+                let language = language.trim();
+                if !language.is_empty() {
+                    crate::ocr::health::OcrHealthChecker::new()
+                        .validate_language(language)
+                        .map_err(|e| DocumentError::BadRequest(format!("Invalid OCR language '{}': {}", language, e)))?;
+                    ocr_language = Some(language.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    capture_service::validate(&metadata, photos.len()).map_err(DocumentError::BadRequest)?;
+
+    // The text layer is recognized in the language the document will be OCRed in
+    let language = match &ocr_language {
+        Some(language) => language.clone(),
+        None => state
+            .db
+            .get_user_settings(auth_user.user.id)
+            .await
+            .ok()
+            .flatten()
+            .map(|settings| settings.ocr_language)
+            .unwrap_or_else(|| "eng".to_string()),
+    };
+
+    let page_count = photos.len();
+    let pdf = capture_service::assemble(photos, &metadata, &language).await.map_err(|e| {
+        if let Some(unusable) = e.downcast_ref::<UnusablePhoto>() {
+            return DocumentError::BadRequest(unusable.to_string());
+        }
+        let message = format!("Failed to assemble capture: {}", e);
+        error!("{}", message);
+        DocumentError::InternalServerError(message)
+    })?;
+
+    let filename = capture_filename(metadata.filename.as_deref(), metadata.captured_at.unwrap_or_else(chrono::Utc::now));
+    let file = UploadedFile { filename, content_type: "application/pdf".to_string(), data: pdf };
+    let uploaded = ingest_upload(&state, &auth_user, &client, file, ocr_language, Vec::new()).await?;
+
+    if uploaded.status == "success" {
+        let details = json!({
+            "pages": page_count,
+            "straightened_pages": metadata
+                .pages
+                .iter()
+                .enumerate()
+                .filter(|(_, page)| page.corners.is_some())
+                .map(|(index, _)| index + 1)
+                .collect::<Vec<_>>(),
+            "enhanced": metadata.enhance.unwrap_or(true),
+            "captured_at": metadata.captured_at,
+            "device": metadata.device,
+        });
+        if let Err(e) = state.db.set_document_metadata_value(uploaded.id, CAPTURE_METADATA_KEY, details).await {
+            warn!("Failed to record capture details of document {}: {}", uploaded.id, e);
+        }
+    }
+
+    info!("User {} uploaded a {} page capture as document {}", auth_user.user.id, page_count, uploaded.id);
+    Ok(Json(uploaded))
+}
+
+/// The capture's file name, with a `.pdf` extension
+fn capture_filename(requested: Option<&str>, captured_at: chrono::DateTime<chrono::Utc>) -> String {
+    let name = requested
+        .map(|name| name.trim().replace(['/', '\\'], "-"))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("capture-{}", captured_at.format("%Y%m%d-%H%M%S")));
+    if name.to_ascii_lowercase().ends_with(".pdf") {
+        name
+    } else {
+        format!("{}.pdf", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_capture_filename() {
+        let captured_at = chrono::Utc.with_ymd_and_hms(2025, 10, 15, 9, 30, 5).unwrap();
+        assert_eq!(capture_filename(None, captured_at), "capture-20251015-093005.pdf");
+        assert_eq!(capture_filename(Some("  "), captured_at), "capture-20251015-093005.pdf");
+        assert_eq!(capture_filename(Some("Lease/2025"), captured_at), "Lease-2025.pdf");
+        assert_eq!(capture_filename(Some("receipt.PDF"), captured_at), "receipt.PDF");
+    }
+}
@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{Document, DocumentCompareQuery, DocumentCompareResponse, SharePermission},
+    services::document_compare_service::{compare_texts, DocumentCompareService},
+    AppState,
+};
+
+async fn load_document(state: &AppState, auth_user: &AuthUser, document_id: Uuid) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Compare two documents: a line diff of their OCR texts and, for PDFs and images,
+/// how different each page looks, e.g. to find what changed between two revisions
+/// of a contract or between two scans of the same paper
+#[utoipa::path(
+    get,
+    path = "/api/documents/compare",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(DocumentCompareQuery),
+    responses(
+        (status = 200, description = "Text diff from a to b and page difference scores", body = DocumentCompareResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Either document not found"),
+        (status = 409, description = "Either document has no OCR text yet"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn compare_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<DocumentCompareQuery>,
+) -> Result<Json<DocumentCompareResponse>, StatusCode> {
+    let a = load_document(&state, &auth_user, query.a).await?;
+    let b = load_document(&state, &auth_user, query.b).await?;
+    let a_text = a.ocr_text.as_deref().ok_or(StatusCode::CONFLICT)?;
+    let b_text = b.ocr_text.as_deref().ok_or(StatusCode::CONFLICT)?;
+
+    let mut comparison = compare_texts(a.id, b.id, a_text, b_text);
+    match DocumentCompareService::new(state.file_service.as_ref().clone()).compare_pages(&a, &b).await {
+        Ok(pages) => {
+            comparison.pages_compared = true;
+            comparison.pages = pages;
+        }
+        Err(e) => warn!("Could not compare the pages of documents {} and {}: {}", a.id, b.id, e),
+    }
+
+    Ok(Json(comparison))
+}
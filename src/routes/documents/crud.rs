@@ -1,6 +1,9 @@
 use axum::{
-    extract::{Multipart, Path, Query, State},
-    http::{StatusCode, header::CONTENT_TYPE},
+    extract::{Multipart, OriginalUri, Path, Query, State},
+    http::{
+        header::{ACCEPT_RANGES, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, RANGE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
     response::{Json, Response, IntoResponse},
     body::Body,
 };
@@ -10,40 +13,64 @@ use tracing::{debug, error, info, warn};
 
 use crate::{
     auth::AuthUser,
+    embedded_metadata,
     ingestion::document_ingestion::{DocumentIngestionService, IngestionResult},
-    models::DocumentResponse,
+    mime_detection::{self, UnsupportedFileType},
+    db::workspaces::WorkspaceScoped,
+    models::{DocumentResponse, SharePermission, WorkspaceRole},
+    services::audit_service::{self, document_details, AuditEvent, ClientInfo},
+    services::file_service::FileService,
+    services::ingestion_hook_service::IngestionHookRejected,
+    services::malware_scan_service::MalwareDetected,
+    services::pdf_security_service,
+    services::storage_quota_service::StorageQuotaExceeded,
+    services::workspace_service,
+    storage::FileStream,
+    utils::conditional::{self, Validators},
+    utils::http_range::{self, ByteRange},
+    utils::pagination::{Cursor, Page, PageRequest},
     AppState,
 };
-use super::types::{PaginationQuery, DocumentUploadResponse, PaginatedDocumentsResponse, DocumentPaginationInfo};
+use super::types::{PaginationQuery, DocumentUploadResponse, PaginatedDocumentsResponse, DocumentPaginationInfo, RenameDocumentRequest};
 
 /// Custom error type for document operations
 #[derive(Debug)]
 pub enum DocumentError {
     BadRequest(String),
+    Forbidden(String),
     NotFound,
     Conflict(String),
     PayloadTooLarge(String),
+    QuotaExceeded(String),
     InternalServerError(String),
     UploadTimeout(String),
     DatabaseConstraintViolation(String),
     OcrProcessingError(String),
     FileProcessingError(String),
     ConcurrentUploadError(String),
+    MalwareDetected(String),
+    UnsupportedMediaType(String),
+    RejectedByHook(String),
 }
 
 impl IntoResponse for DocumentError {
     fn into_response(self) -> Response {
         let (status, message, error_code) = match self {
             DocumentError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg, "UPLOAD_BAD_REQUEST"),
+            DocumentError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg, "UPLOAD_FORBIDDEN"),
             DocumentError::NotFound => (StatusCode::NOT_FOUND, "Document not found".to_string(), "UPLOAD_NOT_FOUND"),
             DocumentError::Conflict(msg) => (StatusCode::CONFLICT, msg, "UPLOAD_CONFLICT"),
             DocumentError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg, "UPLOAD_TOO_LARGE"),
+            DocumentError::QuotaExceeded(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg, "UPLOAD_QUOTA_EXCEEDED"),
             DocumentError::InternalServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg, "UPLOAD_INTERNAL_ERROR"),
             DocumentError::UploadTimeout(msg) => (StatusCode::REQUEST_TIMEOUT, msg, "UPLOAD_TIMEOUT"),
             DocumentError::DatabaseConstraintViolation(msg) => (StatusCode::CONFLICT, msg, "UPLOAD_DB_CONSTRAINT"),
             DocumentError::OcrProcessingError(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg, "UPLOAD_OCR_ERROR"),
             DocumentError::FileProcessingError(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg, "UPLOAD_FILE_PROCESSING_ERROR"),
             DocumentError::ConcurrentUploadError(msg) => (StatusCode::TOO_MANY_REQUESTS, msg, "UPLOAD_CONCURRENT_ERROR"),
+            DocumentError::MalwareDetected(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg, "UPLOAD_MALWARE_DETECTED"),
+            DocumentError::UnsupportedMediaType(msg) => (StatusCode::UNSUPPORTED_MEDIA_TYPE, msg, "UPLOAD_UNSUPPORTED_TYPE"),
+            DocumentError::RejectedByHook(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg, "UPLOAD_REJECTED_BY_HOOK"),
         };
         
         (status, Json(json!({
@@ -69,13 +96,17 @@ impl IntoResponse for DocumentError {
         (status = 200, description = "Document uploaded successfully", body = DocumentUploadResponse),
         (status = 400, description = "Bad request"),
         (status = 401, description = "Unauthorized"),
-        (status = 413, description = "File too large"),
+        (status = 403, description = "The workspace named by X-Workspace-Id requires the editor role to upload"),
+        (status = 413, description = "File too large, or it does not fit in the user's storage quota"),
+        (status = 415, description = "The file's content is not an accepted type"),
+        (status = 422, description = "Malware detected; the file was quarantined"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn upload_document(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     mut multipart: Multipart,
 ) -> Result<Json<DocumentUploadResponse>, DocumentError> {
     let mut uploaded_file = None;
@@ -142,7 +173,9 @@ pub async fn upload_document(
                 })?
                 .to_string();
             
-            let content_type = field.content_type()
+            // Browsers rarely send a usable type for .eml/.msg files
+            let content_type = crate::ocr::email_parser::email_mime_type(&filename)
+                .or(field.content_type())
                 .unwrap_or("application/octet-stream")
                 .to_string();
             
@@ -161,6 +194,39 @@ pub async fn upload_document(
         error!("{}", error_msg);
         DocumentError::BadRequest(error_msg)
     })?;
+
+    let file = UploadedFile { filename, content_type, data };
+    ingest_upload(&state, &auth_user, &client, file, ocr_language, ocr_languages).await.map(Json)
+}
+
+/// A file received by an upload endpoint, before ingestion
+pub(crate) struct UploadedFile {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Ingest an uploaded file like `POST /api/documents` does: check its size, store it,
+/// queue it for OCR and remember the OCR languages it was uploaded with
+pub(crate) async fn ingest_upload(
+    state: &Arc<AppState>,
+    auth_user: &AuthUser,
+    client: &ClientInfo,
+    file: UploadedFile,
+    ocr_language: Option<String>,
+    ocr_languages: Vec<String>,
+) -> Result<DocumentUploadResponse, DocumentError> {
+    let UploadedFile { filename, content_type, data } = file;
+
+    // Uploading into a workspace takes at least the editor role there
+    if let Some(workspace_id) = auth_user.workspace_id {
+        let allowed = workspace_service::has_role(&state.db, &auth_user.user, workspace_id, WorkspaceRole::Editor)
+            .await
+            .map_err(|e| DocumentError::InternalServerError(format!("Failed to look up workspace role: {}", e)))?;
+        if !allowed {
+            return Err(DocumentError::Forbidden("Uploading into this workspace requires the editor role".to_string()));
+        }
+    }
     
     // Validate file size against configured limit
     let max_file_size_bytes = state.config.max_file_size_mb as usize * 1024 * 1024;
@@ -195,8 +261,9 @@ pub async fn upload_document(
         metadata: None, // Will be populated with extracted metadata below
     };
     
-    // Extract content-based metadata from uploaded file
-    if let Ok(Some(content_metadata)) = crate::metadata_extraction::extract_content_metadata(&data, &content_type, &filename).await {
+    // Extract content-based metadata as what the file is, not what it claims to be
+    let detected_mime_type = mime_detection::detect_upload_mime(&data, &filename, Some(&content_type)).mime_type;
+    if let Ok(Some(content_metadata)) = crate::metadata_extraction::extract_content_metadata(&data, &detected_mime_type, &filename).await {
         file_info.metadata = Some(content_metadata);
     }
     
@@ -210,16 +277,20 @@ pub async fn upload_document(
     debug!("[UPLOAD_DEBUG] Calling ingestion service for file: {}", filename);
     let ingestion_start = std::time::Instant::now();
     
-    match ingestion_service.ingest_from_file_info(
+    // The file is sealed with the workspace's key before the document is placed in it
+    let ingested = workspace_service::within(auth_user.workspace_id, ingestion_service.ingest_from_file_info(
         &file_info, 
         data, 
         auth_user.user.id, 
         crate::ingestion::document_ingestion::DeduplicationPolicy::Skip, 
         "web_upload", 
         None
-    ).await {
-        Ok(IngestionResult::Created(document)) => {
+    )).await;
+    match ingested {
+        // Upload paths are virtual, so uploads never become a new version
+        Ok(IngestionResult::Created(document)) | Ok(IngestionResult::NewVersion(document)) => {
             info!("Document uploaded successfully: {}", document.id);
+            workspace_service::place_in_workspace(&state.db, WorkspaceScoped::Document, document.id, auth_user.workspace_id).await;
             
             // Update user's OCR language settings based on what was provided
             if !ocr_languages.is_empty() {
@@ -261,25 +332,49 @@ pub async fn upload_document(
                 info!("Document {} enqueued for OCR processing", document.id);
             }
             
-            Ok(Json(DocumentUploadResponse {
+            crate::services::event_service::EventService::new(state.db.clone())
+                .publish_best_effort(
+                    crate::models::EventType::DocumentCreated,
+                    Some(auth_user.user.id),
+                    Some(document.id),
+                    json!({
+                        "document_id": document.id,
+                        "filename": document.filename,
+                        "mime_type": document.mime_type,
+                        "file_size": document.file_size,
+                        "source_type": document.source_type,
+                        "source_id": document.source_id,
+                    }),
+                )
+                .await;
+            audit_service::record(
+                &state.db,
+                Some(&auth_user.user),
+                client,
+                AuditEvent::new(audit_service::DOCUMENT_UPLOAD, "document", Some(document.id))
+                    .details(document_details(&document)),
+            )
+            .await;
+            
+            Ok(DocumentUploadResponse {
                 id: document.id,
                 filename: document.filename,
                 file_size: document.file_size,
                 mime_type: document.mime_type,
                 status: "success".to_string(),
                 message: "Document uploaded successfully".to_string(),
-            }))
+            })
         }
         Ok(IngestionResult::ExistingDocument(existing_doc)) => {
             warn!("Duplicate document upload attempted: {}", existing_doc.id);
-            Ok(Json(DocumentUploadResponse {
+            Ok(DocumentUploadResponse {
                 id: existing_doc.id,
                 filename: existing_doc.filename,
                 file_size: existing_doc.file_size,
                 mime_type: existing_doc.mime_type,
                 status: "duplicate".to_string(),
                 message: "Document already exists".to_string(),
-            }))
+            })
         }
         Ok(IngestionResult::Skipped { existing_document_id, reason }) => {
             let error_msg = format!("Document upload skipped - {}: {}", reason, existing_document_id);
@@ -295,6 +390,19 @@ pub async fn upload_document(
             let ingestion_duration = ingestion_start.elapsed();
             let error_msg = format!("Failed to ingest document: {} (failed after {:?})", e, ingestion_duration);
             error!("[UPLOAD_DEBUG] {}", error_msg);
+
+            if let Some(detected) = e.downcast_ref::<MalwareDetected>() {
+                return Err(DocumentError::MalwareDetected(detected.to_string()));
+            }
+            if let Some(unsupported) = e.downcast_ref::<UnsupportedFileType>() {
+                return Err(DocumentError::UnsupportedMediaType(unsupported.to_string()));
+            }
+            if let Some(rejected) = e.downcast_ref::<IngestionHookRejected>() {
+                return Err(DocumentError::RejectedByHook(rejected.to_string()));
+            }
+            if let Some(exceeded) = e.downcast_ref::<StorageQuotaExceeded>() {
+                return Err(DocumentError::QuotaExceeded(exceeded.to_string()));
+            }
             
             // Categorize the error for better client handling
             if e.to_string().contains("constraint") || e.to_string().contains("duplicate") {
@@ -332,11 +440,12 @@ pub async fn upload_document(
 pub async fn get_document_by_id(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Path(document_id): Path<uuid::Uuid>,
 ) -> Result<Json<DocumentResponse>, StatusCode> {
     let document = state
         .db
-        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
         .await
         .map_err(|e| {
             error!("Database error getting document {}: {}", document_id, e);
@@ -365,9 +474,40 @@ pub async fn get_document_by_id(
         })?
         .map(|user| user.username);
 
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_VIEW, "document", Some(document.id))
+            .details(json!({ "via": "details", "document": document_details(&document) })),
+    )
+    .await;
+
+    let relations = super::relations::linked_relations(&state, &auth_user.user, document_id).await?;
+    let suggested_relations = super::relations::relation_suggestions(
+        &state,
+        &auth_user.user,
+        &document,
+        super::relations::DEFAULT_MIN_SHARED_ENTITIES,
+        super::relations::DETAIL_RELATION_SUGGESTIONS,
+    )
+    .await?;
+
+    let processing_costs = state
+        .db
+        .get_document_processing_costs(document_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get processing costs of document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     let mut response = DocumentResponse::from(document);
     response.labels = labels;
     response.username = username;
+    response.relations = relations;
+    response.suggested_relations = suggested_relations;
+    response.processing_costs = Some(processing_costs);
 
     Ok(Json(response))
 }
@@ -382,7 +522,8 @@ pub async fn get_document_by_id(
     ),
     params(PaginationQuery),
     responses(
-        (status = 200, description = "Paginated list of documents", body = PaginatedDocumentsResponse),
+        (status = 200, description = "Paginated list of documents, newest first, with a `Link` to the next page and `X-Total-Count`", body = PaginatedDocumentsResponse),
+        (status = 400, description = "Invalid cursor, or a cursor combined with an offset"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     )
@@ -390,61 +531,40 @@ pub async fn get_document_by_id(
 pub async fn list_documents(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    OriginalUri(uri): OriginalUri,
     Query(query): Query<PaginationQuery>,
-) -> Result<Json<PaginatedDocumentsResponse>, StatusCode> {
-    let limit = query.limit.unwrap_or(25);
-    let offset = query.offset.unwrap_or(0);
+) -> Result<(HeaderMap, Json<PaginatedDocumentsResponse>), StatusCode> {
+    let page = PageRequest::parse(query.limit, 25, 1000, query.cursor.as_deref(), query.offset, query.include_total)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let ocr_status = query.ocr_status.as_deref();
 
-    // Get total count for pagination
-    let total_count = if let Some(ocr_status) = query.ocr_status.as_deref() {
-        state
+    let total_count = if page.include_total {
+        let total = state
             .db
-            .count_documents_by_user_with_role_and_filter(
-                auth_user.user.id,
-                auth_user.user.role,
-                Some(ocr_status),
-            )
+            .get_documents_count_with_role_and_filter(auth_user.user.id, auth_user.user.role, ocr_status)
             .await
+            .map_err(|e| {
+                error!("Database error counting documents: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        Some(total)
     } else {
-        state
-            .db
-            .count_documents_by_user_with_role(
-                auth_user.user.id,
-                auth_user.user.role,
-            )
-            .await
-    }
-    .map_err(|e| {
-        error!("Database error counting documents: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+        None
+    };
 
-    let documents = if let Some(ocr_status) = query.ocr_status.as_deref() {
-        state
-            .db
-            .get_documents_by_user_with_role_and_filter(
-                auth_user.user.id,
-                auth_user.user.role,
-                Some(ocr_status),
-                limit,
-                offset,
-            )
-            .await
-    } else {
-        state
-            .db
-            .get_documents_by_user_with_role(
-                auth_user.user.id,
-                auth_user.user.role,
-                limit,
-                offset,
-            )
-            .await
-    }
-    .map_err(|e| {
-        error!("Database error listing documents: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    let documents = state
+        .db
+        .get_documents_page(auth_user.user.id, auth_user.user.role, ocr_status, &page)
+        .await
+        .map_err(|e| {
+            error!("Database error listing documents: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let (limit, offset) = (page.limit, page.offset);
+    let page = Page::new(documents, limit, total_count, |document| Cursor::new(document.created_at, document.id));
+    let headers = page.headers(&uri);
+    let next_cursor = page.next_cursor;
+    let documents = page.items;
 
     // Get document IDs for batch label fetching
     let document_ids: Vec<uuid::Uuid> = documents.iter().map(|d| d.id).collect();
@@ -482,13 +602,17 @@ pub async fn list_documents(
         total: total_count,
         limit,
         offset,
-        has_more: offset + limit < total_count,
+        has_more: next_cursor.is_some(),
+        next_cursor,
     };
 
-    Ok(Json(PaginatedDocumentsResponse {
-        documents: responses,
-        pagination,
-    }))
+    Ok((
+        headers,
+        Json(PaginatedDocumentsResponse {
+            documents: responses,
+            pagination,
+        }),
+    ))
 }
 
 /// Delete a specific document
@@ -506,48 +630,199 @@ pub async fn list_documents(
         (status = 204, description = "Document deleted successfully"),
         (status = 404, description = "Document not found"),
         (status = 401, description = "Unauthorized"),
+        (status = 423, description = "The document is on legal hold"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn delete_document(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Path(document_id): Path<uuid::Uuid>,
 ) -> Result<StatusCode, StatusCode> {
     // Get document first to check if it exists and user has access
     let document = state
         .db
-        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Delete)
         .await
         .map_err(|e| {
             error!("Database error getting document {}: {}", document_id, e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?
         .ok_or(StatusCode::NOT_FOUND)?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+
+    let deleted = remove_document(&state, &document, &auth_user).await.map_err(|e| {
+        error!("Database error deleting document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    // Delete from database
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_DELETE, "document", Some(document.id))
+            .details(document_details(&document)),
+    )
+    .await;
+
+    info!("Document deleted successfully: {}", document_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delete a document and its files, queue the deletion on a bidirectional WebDAV
+/// source and publish the event. Returns false when the document was already gone.
+pub(crate) async fn remove_document(
+    state: &AppState,
+    document: &crate::models::Document,
+    auth_user: &AuthUser,
+) -> anyhow::Result<bool> {
+    remove_document_as(state, document, auth_user.user.id, auth_user.user.role).await
+}
+
+/// Same as [`remove_document`] for callers without a request, such as background jobs
+pub(crate) async fn remove_document_as(
+    state: &AppState,
+    document: &crate::models::Document,
+    user_id: uuid::Uuid,
+    user_role: crate::models::UserRole,
+) -> anyhow::Result<bool> {
+    // Version, narration and kept original rows go with the document, so look up
+    // their files first
+    let versions = state.db.get_document_versions(document.id).await?;
+    let audio = state.db.get_document_audio(document.id).await?;
+    let compressed_original = state.db.get_compressed_original(document.id).await?;
     let deleted = state
         .db
-        .delete_document(document_id, auth_user.user.id, auth_user.user.role)
-        .await
-        .map_err(|e| {
-            error!("Database error deleting document {}: {}", document_id, e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .delete_document(document.id, user_id, user_role)
+        .await?;
 
     if !deleted {
-        return Err(StatusCode::NOT_FOUND);
+        return Ok(false);
     }
 
     // Delete associated files
     let file_service = &state.file_service;
-    if let Err(e) = file_service.delete_document_files(&document).await {
-        warn!("Failed to delete files for document {}: {}", document_id, e);
+    if let Err(e) = file_service.delete_document_files(document).await {
+        warn!("Failed to delete files for document {}: {}", document.id, e);
         // Continue anyway - database deletion succeeded
     }
+    for version in &versions {
+        if let Err(e) = file_service
+            .delete_version_file(document.user_id, version.id, &version.filename, &version.file_path)
+            .await
+        {
+            warn!("Failed to delete file of version {} of document {}: {}", version.version_number, document.id, e);
+        }
+    }
+    if let Some(audio) = &audio {
+        crate::services::narration_service::NarrationService::new(state.db.clone(), file_service.as_ref().clone())
+            .delete_audio_file(document.user_id, audio)
+            .await;
+    }
+    if let Some(original) = &compressed_original {
+        let compression = crate::services::storage_compression_service::StorageCompressionService::new(
+            state.db.clone(),
+            file_service.as_ref().clone(),
+        );
+        if let Err(e) = compression.delete_original_file(document.user_id, original).await {
+            warn!("Failed to delete the original of document {}: {}", document.id, e);
+        }
+    }
 
-    info!("Document deleted successfully: {}", document_id);
-    Ok(StatusCode::NO_CONTENT)
+    if let Err(e) = crate::services::webdav::write_back::queue_document_deletion(&state.db, document).await {
+        warn!("Failed to queue WebDAV deletion for document {}: {}", document.id, e);
+    }
+
+    crate::services::event_service::EventService::new(state.db.clone())
+        .publish_best_effort(
+            crate::models::EventType::DocumentDeleted,
+            Some(document.user_id),
+            Some(document.id),
+            json!({
+                "document_id": document.id,
+                "filename": document.filename,
+            }),
+        )
+        .await;
+
+    Ok(true)
+}
+
+/// Rename a document. For bidirectional WebDAV sources the file is renamed on the
+/// server at the next sync.
+#[utoipa::path(
+    put,
+    path = "/api/documents/{id}/rename",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    request_body = RenameDocumentRequest,
+    responses(
+        (status = 200, description = "Renamed document", body = DocumentResponse),
+        (status = 400, description = "Invalid filename"),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 423, description = "The document is on legal hold"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn rename_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+    Json(request): Json<RenameDocumentRequest>,
+) -> Result<Json<DocumentResponse>, StatusCode> {
+    let filename = request.filename.trim();
+    if !is_valid_filename(filename) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Edit)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+
+    let document = apply_rename(&state, &document, filename).await.map_err(|e| {
+        error!("Failed to rename document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!("Document {} renamed to '{}'", document_id, filename);
+    Ok(Json(DocumentResponse::from(document)))
+}
+
+/// A display name without path separators, at most 255 bytes
+pub(crate) fn is_valid_filename(filename: &str) -> bool {
+    !(filename.is_empty() || filename.len() > 255 || filename.contains(['/', '\\']) || filename == "." || filename == "..")
+}
+
+/// Rename a document; for bidirectional WebDAV sources the rename is queued for the server
+pub(crate) async fn apply_rename(
+    state: &AppState,
+    document: &crate::models::Document,
+    filename: &str,
+) -> anyhow::Result<crate::models::Document> {
+    let new_source_path = crate::services::webdav::write_back::queue_document_rename(&state.db, document, filename).await?;
+    let renamed = state.db.rename_document(document.id, filename, new_source_path.as_deref()).await?;
+    crate::services::event_service::EventService::new(state.db.clone())
+        .publish_document_updated(&renamed, &["filename"])
+        .await;
+    Ok(renamed)
 }
 
 /// Download a document file
@@ -559,11 +834,18 @@ pub async fn delete_document(
         ("bearer_auth" = [])
     ),
     params(
-        ("id" = uuid::Uuid, Path, description = "Document ID")
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("Range" = Option<String>, Header, description = "A single byte range, e.g. `bytes=1048576-` to resume a download"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached copy; 304 when it is still current"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Date of a cached copy, used without If-None-Match"),
+        ("If-Range" = Option<String>, Header, description = "ETag or date the Range applies to; the whole file is sent when it changed")
     ),
     responses(
-        (status = 200, description = "Document file", content_type = "application/octet-stream"),
+        (status = 200, description = "Document file; X-Readur-Security-Warning names the active content of a PDF served unsanitized", content_type = "application/octet-stream"),
+        (status = 206, description = "The requested byte range of the file", content_type = "application/octet-stream"),
+        (status = 304, description = "The cached copy is current"),
         (status = 404, description = "Document not found"),
+        (status = 416, description = "Range starts past the end of the file"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     )
@@ -571,11 +853,13 @@ pub async fn delete_document(
 pub async fn download_document(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Path(document_id): Path<uuid::Uuid>,
+    headers: HeaderMap,
 ) -> Result<Response<Body>, StatusCode> {
     let document = state
         .db
-        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
         .await
         .map_err(|e| {
             error!("Database error getting document {}: {}", document_id, e);
@@ -583,25 +867,38 @@ pub async fn download_document(
         })?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    let file_service = &state.file_service;
-    let file_data = file_service
-        .read_file(&document.file_path)
+    let validators = document_validators(&document, auth_user.user.id, None)?;
+    if validators.is_not_modified(&headers) {
+        return validators.not_modified(conditional::REVALIDATE);
+    }
+    let headers = range_headers(headers, &validators);
+
+    let part = open_document_part(&state.file_service, &document, auth_user.user.id, &headers)
         .await
         .map_err(|e| {
             error!("Failed to read document file {}: {}", document_id, e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    let response = Response::builder()
-        .status(StatusCode::OK)
-        .header(CONTENT_TYPE, document.mime_type)
-        .header("Content-Disposition", format!("attachment; filename=\"{}\"", document.original_filename))
-        .header("Content-Length", file_data.len().to_string())
-        .body(Body::from(file_data))
-        .map_err(|e| {
-            error!("Failed to build response: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    if part.has_content() {
+        audit_service::record_file_access(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::DOCUMENT_DOWNLOAD, "document", Some(document.id))
+                .details(document_details(&document)),
+        )
+        .await;
+    }
+
+    let disposition = format!("attachment; filename=\"{}\"", document.original_filename);
+    let mut response = with_validators(file_part_response(part, &document.mime_type, Some(&disposition))?, &validators);
+    // Originals with scripts or attachments in them are served, but flagged
+    if let Some(warning) = pdf_security_service::download_warning(&document) {
+        if let Ok(value) = HeaderValue::from_str(&warning) {
+            response.headers_mut().insert(pdf_security_service::SECURITY_WARNING_HEADER, value);
+        }
+    }
 
     debug!("Document downloaded: {}", document_id);
     Ok(response)
@@ -616,11 +913,18 @@ pub async fn download_document(
         ("bearer_auth" = [])
     ),
     params(
-        ("id" = uuid::Uuid, Path, description = "Document ID")
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("Range" = Option<String>, Header, description = "A single byte range, e.g. for PDF.js to load pages lazily"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached copy; 304 when it is still current"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Date of a cached copy, used without If-None-Match"),
+        ("If-Range" = Option<String>, Header, description = "ETag or date the Range applies to; the whole file is sent when it changed")
     ),
     responses(
         (status = 200, description = "Document file for viewing", content_type = "application/octet-stream"),
+        (status = 206, description = "The requested byte range of the file", content_type = "application/octet-stream"),
+        (status = 304, description = "The cached copy is current"),
         (status = 404, description = "Document not found"),
+        (status = 416, description = "Range starts past the end of the file"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     )
@@ -628,11 +932,13 @@ pub async fn download_document(
 pub async fn view_document(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Path(document_id): Path<uuid::Uuid>,
+    headers: HeaderMap,
 ) -> Result<Response<Body>, StatusCode> {
     let document = state
         .db
-        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
         .await
         .map_err(|e| {
             error!("Database error getting document {}: {}", document_id, e);
@@ -640,29 +946,292 @@ pub async fn view_document(
         })?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    let file_service = &state.file_service;
-    let file_data = file_service
-        .read_file(&document.file_path)
+    let validators = document_validators(&document, auth_user.user.id, None)?;
+    if validators.is_not_modified(&headers) {
+        return validators.not_modified(conditional::REVALIDATE);
+    }
+    let headers = range_headers(headers, &validators);
+
+    let part = open_document_part(&state.file_service, &document, auth_user.user.id, &headers)
         .await
         .map_err(|e| {
             error!("Failed to read document file {}: {}", document_id, e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    let response = Response::builder()
-        .status(StatusCode::OK)
-        .header(CONTENT_TYPE, document.mime_type)
-        .header("Content-Length", file_data.len().to_string())
-        .body(Body::from(file_data))
+    if part.has_content() {
+        audit_service::record_file_access(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::DOCUMENT_VIEW, "document", Some(document.id))
+                .details(json!({ "via": "file", "document": document_details(&document) })),
+        )
+        .await;
+    }
+
+    let response = with_validators(file_part_response(part, &document.mime_type, None)?, &validators);
+
+    debug!("Document viewed: {}", document_id);
+    Ok(response)
+}
+
+/// Preview a document in the browser. PDFs and images are served as stored;
+/// office documents are served as a cached PDF rendition.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/preview",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("Range" = Option<String>, Header, description = "A single byte range, e.g. for PDF.js to load pages lazily"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached copy; 304 when it is still current"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Date of a cached copy, used without If-None-Match"),
+        ("If-Range" = Option<String>, Header, description = "ETag or date the Range applies to; the whole file is sent when it changed")
+    ),
+    responses(
+        (status = 200, description = "Document or its PDF rendition for previewing", content_type = "application/pdf"),
+        (status = 206, description = "The requested byte range of the preview", content_type = "application/pdf"),
+        (status = 304, description = "The cached copy is current"),
+        (status = 404, description = "Document not found"),
+        (status = 416, description = "Range starts past the end of the preview"),
+        (status = 401, description = "Unauthorized"),
+        (status = 415, description = "Document format cannot be previewed"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn preview_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(document_id): Path<uuid::Uuid>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
+        .await
         .map_err(|e| {
-            error!("Failed to build response: {}", e);
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Mislabeled files are rendered as what their content is
+    let extension = mime_detection::rendering_extension(&document.original_filename, &document.mime_type);
+    let renders_pdf = crate::services::office_preview::is_office_extension(&extension);
+
+    let validators = document_validators(&document, auth_user.user.id, renders_pdf.then_some("preview"))?;
+    if validators.is_not_modified(&headers) {
+        return validators.not_modified(conditional::REVALIDATE);
+    }
+    let headers = range_headers(headers, &validators);
+
+    let (content_type, data) = if renders_pdf {
+        let data = state
+            .file_service
+            .get_or_generate_preview_pdf(&document.file_path, &document.original_filename, &document.mime_type)
+            .await
+            .map_err(|e| {
+                error!("Failed to render preview for document {}: {}", document_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        ("application/pdf".to_string(), data)
+    } else if document.mime_type == "application/pdf"
+        || document.mime_type.starts_with("image/")
+        || document.mime_type.starts_with("text/")
+    {
+        let data = state.file_service.read_file(&document.file_path).await.map_err(|e| {
+            error!("Failed to read document file {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        let strip = strips_metadata_for(&document, auth_user.user.id).map_err(|e| {
+            error!("Invalid metadata stripping configuration: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+        let data = if strip {
+            embedded_metadata::strip(&data, &document.mime_type).unwrap_or(data)
+        } else {
+            data
+        };
+        (document.mime_type.clone(), data)
+    } else {
+        return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    };
 
-    debug!("Document viewed: {}", document_id);
+    let part = FilePart::cut(FileStream::from_bytes(data), range_header(&headers));
+    if part.has_content() {
+        audit_service::record_file_access(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::DOCUMENT_VIEW, "document", Some(document.id))
+                .details(json!({ "via": "preview", "document": document_details(&document) })),
+        )
+        .await;
+    }
+
+    let response = with_validators(file_part_response(part, &content_type, Some("inline"))?, &validators);
+
+    debug!("Document previewed: {}", document_id);
     Ok(response)
 }
 
+/// A stored file, or the part of it a `Range` header asks for
+pub(crate) enum FilePart {
+    Whole(FileStream),
+    Partial { stream: FileStream, start: u64, end: u64, total: u64 },
+    Unsatisfiable { total: u64 },
+}
+
+impl FilePart {
+    /// The part of an already opened file that `range` asks for
+    fn cut(stream: FileStream, range: Option<&str>) -> Self {
+        let (Some(range), Some(total)) = (range, stream.size) else {
+            return FilePart::Whole(stream);
+        };
+        match http_range::parse(range, total) {
+            ByteRange::Full => FilePart::Whole(stream),
+            ByteRange::Partial { start, end } => FilePart::Partial { stream: stream.slice(start, end), start, end, total },
+            ByteRange::Unsatisfiable => FilePart::Unsatisfiable { total },
+        }
+    }
+
+    /// Whether the response carries file content, a 200 or 206
+    pub(crate) fn has_content(&self) -> bool {
+        !matches!(self, FilePart::Unsatisfiable { .. })
+    }
+}
+
+fn range_header(headers: &HeaderMap) -> Option<&str> {
+    headers.get(RANGE).and_then(|value| value.to_str().ok())
+}
+
+/// Open a stored file, or only the part of it the `Range` header asks for
+pub(crate) async fn open_file_part(
+    file_service: &FileService,
+    file_path: &str,
+    headers: &HeaderMap,
+) -> anyhow::Result<FilePart> {
+    let Some(range) = range_header(headers) else {
+        return Ok(FilePart::Whole(file_service.open_stream(file_path).await?));
+    };
+    // Encrypted files are decrypted in memory; cut the part from the whole file
+    if file_service.encryption().is_some() {
+        return Ok(FilePart::cut(file_service.open_stream(file_path).await?, Some(range)));
+    }
+
+    let Some(total) = file_service.file_size(file_path).await? else {
+        return Ok(FilePart::Whole(file_service.open_stream(file_path).await?));
+    };
+    Ok(match http_range::parse(range, total) {
+        ByteRange::Full => FilePart::Whole(file_service.open_stream(file_path).await?),
+        ByteRange::Partial { start, end } => FilePart::Partial {
+            stream: file_service.open_range_stream(file_path, start, end).await?,
+            start,
+            end,
+            total,
+        },
+        ByteRange::Unsatisfiable => FilePart::Unsatisfiable { total },
+    })
+}
+
+/// Whether the copy served to `user_id` loses its identifying metadata: the document
+/// is someone else's and the policy strips shared copies
+fn strips_metadata_for(document: &crate::models::Document, user_id: uuid::Uuid) -> anyhow::Result<bool> {
+    let policy = embedded_metadata::configured().map_err(anyhow::Error::msg)?;
+    Ok(policy.strips_shared() && document.user_id != user_id && embedded_metadata::can_strip(&document.mime_type))
+}
+
+/// Validators of a document's file as served to `user_id`, or of the `variant`
+/// rendered from it. Copies without metadata are a representation of their own.
+fn document_validators(
+    document: &crate::models::Document,
+    user_id: uuid::Uuid,
+    variant: Option<&str>,
+) -> Result<Validators, StatusCode> {
+    let strip = strips_metadata_for(document, user_id).map_err(|e| {
+        error!("Invalid metadata stripping configuration: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let variant = variant.or(strip.then_some("stripped"));
+    Ok(Validators::for_content(document.file_hash.as_deref(), variant, document.updated_at))
+}
+
+/// The request headers without `Range` when `If-Range` names another representation
+pub(crate) fn range_headers(mut headers: HeaderMap, validators: &Validators) -> HeaderMap {
+    if !validators.range_applies(&headers) {
+        headers.remove(RANGE);
+    }
+    headers
+}
+
+/// Add the validators of the served file, for revalidating cached copies later
+pub(crate) fn with_validators(mut response: Response<Body>, validators: &Validators) -> Response<Body> {
+    validators.write(response.headers_mut());
+    response.headers_mut().insert(CACHE_CONTROL, HeaderValue::from_static(conditional::REVALIDATE));
+    response
+}
+
+/// Open a document's file as served to `user_id`: a stripped copy when
+/// `strips_metadata_for` says so, otherwise as `open_file_part` does
+async fn open_document_part(
+    file_service: &FileService,
+    document: &crate::models::Document,
+    user_id: uuid::Uuid,
+    headers: &HeaderMap,
+) -> anyhow::Result<FilePart> {
+    if !strips_metadata_for(document, user_id)? {
+        return open_file_part(file_service, &document.file_path, headers).await;
+    }
+    let data = file_service.read_file(&document.file_path).await?;
+    let data = embedded_metadata::strip(&data, &document.mime_type).unwrap_or(data);
+    Ok(FilePart::cut(FileStream::from_bytes(data), range_header(headers)))
+}
+
+/// 200 with the whole file, 206 with the requested part or 416
+pub(crate) fn file_part_response(part: FilePart, content_type: &str, disposition: Option<&str>) -> Result<Response<Body>, StatusCode> {
+    let mut response = Response::builder().header(ACCEPT_RANGES, "bytes");
+    let body = match part {
+        FilePart::Whole(stream) => {
+            response = response.status(StatusCode::OK).header(CONTENT_TYPE, content_type);
+            if let Some(size) = stream.size {
+                response = response.header(CONTENT_LENGTH, size.to_string());
+            }
+            Body::from_stream(stream.chunks)
+        }
+        FilePart::Partial { stream, start, end, total } => {
+            response = response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_TYPE, content_type)
+                .header(CONTENT_LENGTH, (end - start + 1).to_string())
+                .header(CONTENT_RANGE, http_range::content_range(start, end, total));
+            Body::from_stream(stream.chunks)
+        }
+        FilePart::Unsatisfiable { total } => {
+            return Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, http_range::unsatisfied_content_range(total))
+                .body(Body::empty())
+                .map_err(|e| {
+                    error!("Failed to build response: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                });
+        }
+    };
+    if let Some(disposition) = disposition {
+        response = response.header(CONTENT_DISPOSITION, disposition);
+    }
+
+    response.body(body).map_err(|e| {
+        error!("Failed to build response: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
 /// Get user's duplicate documents
 #[utoipa::path(
     get,
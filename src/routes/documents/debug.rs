@@ -1,6 +1,6 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::Json,
 };
 use std::sync::Arc;
@@ -8,6 +8,8 @@ use tracing::{debug, error, info};
 
 use crate::{
     auth::AuthUser,
+    models::SharePermission,
+    utils::conditional::Validators,
     AppState,
 };
 use super::types::DocumentDebugInfo;
@@ -113,6 +115,9 @@ pub async fn get_document_debug_info(
     Ok(Json(debug_info))
 }
 
+/// Thumbnails are cached for an hour, then revalidated with their ETag
+const THUMBNAIL_CACHE_CONTROL: &str = "public, max-age=3600";
+
 /// Get thumbnail for a document (if available)
 #[utoipa::path(
     get,
@@ -122,10 +127,13 @@ pub async fn get_document_debug_info(
         ("bearer_auth" = [])
     ),
     params(
-        ("id" = uuid::Uuid, Path, description = "Document ID")
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached thumbnail; 304 when it is still current"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Date of a cached thumbnail, used without If-None-Match")
     ),
     responses(
         (status = 200, description = "Document thumbnail", content_type = "image/jpeg"),
+        (status = 304, description = "The cached thumbnail is current"),
         (status = 404, description = "Document or thumbnail not found"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
@@ -135,10 +143,11 @@ pub async fn get_document_thumbnail(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
     Path(document_id): Path<uuid::Uuid>,
+    headers: HeaderMap,
 ) -> Result<axum::response::Response, StatusCode> {
     let document = state
         .db
-        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
         .await
         .map_err(|e| {
             error!("Database error getting document {}: {}", document_id, e);
@@ -146,22 +155,47 @@ pub async fn get_document_thumbnail(
         })?
         .ok_or(StatusCode::NOT_FOUND)?;
 
+    // Thumbnails are rendered from the file with its first page turned upright, so they
+    // change with its content and that page's transform
+    let transform = state
+        .db
+        .get_page_transforms(document_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get page transforms of document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .find(|t| t.page_number == 1 && !t.is_identity());
+    let variant = match &transform {
+        Some(t) => format!("thumbnail-{}-{}", t.rotation, t.skew_degrees),
+        None => "thumbnail".to_string(),
+    };
+    let validators = Validators::for_content(document.file_hash.as_deref(), Some(&variant), document.updated_at);
+    if validators.is_not_modified(&headers) {
+        return validators.not_modified(THUMBNAIL_CACHE_CONTROL);
+    }
+
     let file_service = &state.file_service;
     
     // Use the FileService to get or generate thumbnail
     #[cfg(feature = "ocr")]
-    match file_service.get_or_generate_thumbnail(&document.file_path, &document.original_filename).await {
+    match file_service
+        .get_or_generate_transformed_thumbnail(&document.file_path, &document.original_filename, &document.mime_type, transform.as_ref())
+        .await
+    {
         Ok(data) => {
-            let response = axum::response::Response::builder()
+            let mut response = axum::response::Response::builder()
                 .status(StatusCode::OK)
                 .header("Content-Type", "image/jpeg")
                 .header("Content-Length", data.len().to_string())
-                .header("Cache-Control", "public, max-age=3600") // Cache for 1 hour
+                .header("Cache-Control", THUMBNAIL_CACHE_CONTROL)
                 .body(axum::body::Body::from(data))
                 .map_err(|e| {
                     error!("Failed to build thumbnail response: {}", e);
                     StatusCode::INTERNAL_SERVER_ERROR
                 })?;
+            validators.write(response.headers_mut());
 
             debug!("Thumbnail served for document: {}", document_id);
             Ok(response)
@@ -5,13 +5,15 @@ use axum::{
     body::Body,
 };
 use std::sync::Arc;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 use std::collections::HashMap;
 use sqlx::Row;
 
 use crate::{
     auth::AuthUser,
-    models::UserRole,
+    models::{FailedDocumentHistory, FailedDocumentRequeue, FailureCategory, UserRole},
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::failed_document_service::{self, FailedDocumentService, RequeueError},
     AppState,
 };
 use super::types::FailedDocumentsQuery;
@@ -28,10 +30,13 @@ use super::types::FailedDocumentsQuery;
         ("limit" = Option<i64>, Query, description = "Number of documents to return"),
         ("offset" = Option<i64>, Query, description = "Number of documents to skip"),
         ("stage" = Option<String>, Query, description = "Filter by failure stage (ocr, ingestion, validation, etc.)"),
-        ("reason" = Option<String>, Query, description = "Filter by failure reason")
+        ("reason" = Option<String>, Query, description = "Filter by failure reason"),
+        ("category" = Option<String>, Query, description = "Filter by failure category (duplicate, unsupported_format, damaged_file, low_ocr_confidence, resource_limit, storage, access, security, other)"),
+        ("include_requeued" = Option<bool>, Query, description = "Also list failures that were requeued")
     ),
     responses(
         (status = 200, description = "Failed documents list", body = serde_json::Value),
+        (status = 400, description = "Invalid failure category"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     )
@@ -43,6 +48,17 @@ pub async fn get_failed_documents(
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     let limit = params.limit.unwrap_or(25);
     let offset = params.offset.unwrap_or(0);
+    let category_reasons: Option<Vec<String>> = params
+        .category
+        .as_deref()
+        .map(|category| {
+            category
+                .parse::<FailureCategory>()
+                .map(|category| category.reasons().iter().map(|reason| reason.to_string()).collect())
+        })
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let requeued_filter = if params.include_requeued.unwrap_or(false) { "" } else { " AND requeued_at IS NULL" };
     
     // Query the unified failed_documents table
     let mut query_builder = sqlx::QueryBuilder::new(
@@ -50,11 +66,13 @@ pub async fn get_failed_documents(
         SELECT id, filename, original_filename, file_path, file_size, mime_type,
                content, tags, ocr_text, ocr_confidence, ocr_word_count, ocr_processing_time_ms,
                failure_reason, failure_stage, error_message, existing_document_id,
-               ingestion_source, retry_count, last_retry_at, created_at, updated_at
+               ingestion_source, retry_count, last_retry_at, requeued_at, requeued_document_id,
+               created_at, updated_at
         FROM failed_documents
         WHERE ($1::uuid IS NULL OR user_id = $1)
         "#
     );
+    query_builder.push(requeued_filter);
     
     let mut bind_count = 1;
     
@@ -70,6 +88,11 @@ pub async fn get_failed_documents(
         query_builder.push(&format!(" AND failure_reason = ${}", bind_count));
     }
     
+    if category_reasons.is_some() {
+        bind_count += 1;
+        query_builder.push(&format!(" AND failure_reason = ANY(${})", bind_count));
+    }
+    
     query_builder.push(" ORDER BY created_at DESC");
     query_builder.push(&format!(" LIMIT ${} OFFSET ${}", bind_count + 1, bind_count + 2));
     
@@ -90,6 +113,10 @@ pub async fn get_failed_documents(
         query = query.bind(reason);
     }
     
+    if let Some(reasons) = &category_reasons {
+        query = query.bind(reasons.clone());
+    }
+    
     query = query.bind(limit).bind(offset);
     
     let failed_docs = query
@@ -104,6 +131,7 @@ pub async fn get_failed_documents(
     let mut count_query_builder = sqlx::QueryBuilder::new(
         "SELECT COUNT(*) FROM failed_documents WHERE ($1::uuid IS NULL OR user_id = $1)"
     );
+    count_query_builder.push(requeued_filter);
     
     let mut count_bind_count = 1;
     
@@ -117,6 +145,11 @@ pub async fn get_failed_documents(
         count_query_builder.push(&format!(" AND failure_reason = ${}", count_bind_count));
     }
     
+    if category_reasons.is_some() {
+        count_bind_count += 1;
+        count_query_builder.push(&format!(" AND failure_reason = ANY(${})", count_bind_count));
+    }
+    
     let mut count_query = count_query_builder.build_query_scalar::<i64>();
     
     count_query = count_query.bind(if auth_user.user.role == UserRole::Admin { 
@@ -133,6 +166,10 @@ pub async fn get_failed_documents(
         count_query = count_query.bind(reason);
     }
     
+    if let Some(reasons) = &category_reasons {
+        count_query = count_query.bind(reasons.clone());
+    }
+    
     let total_count = count_query
         .fetch_one(state.db.get_pool())
         .await
@@ -140,6 +177,11 @@ pub async fn get_failed_documents(
     
     // Convert to JSON response format
     let documents: Vec<serde_json::Value> = failed_docs.iter().map(|row| {
+        let failure_reason = row.get::<String, _>("failure_reason");
+        let category = categorize_failure_reason(
+            Some(&failure_reason),
+            row.get::<Option<String>, _>("error_message").as_deref()
+        );
         serde_json::json!({
             "id": row.get::<uuid::Uuid, _>("id"),
             "filename": row.get::<String, _>("filename"),
@@ -160,14 +202,16 @@ pub async fn get_failed_documents(
             "ingestion_source": row.get::<String, _>("ingestion_source"),
             "retry_count": row.get::<Option<i32>, _>("retry_count"),
             "last_retry_at": row.get::<Option<chrono::DateTime<chrono::Utc>>, _>("last_retry_at"),
+            "requeued_at": row.get::<Option<chrono::DateTime<chrono::Utc>>, _>("requeued_at"),
+            "requeued_document_id": row.get::<Option<uuid::Uuid>, _>("requeued_document_id"),
             "created_at": row.get::<chrono::DateTime<chrono::Utc>, _>("created_at"),
             "updated_at": row.get::<chrono::DateTime<chrono::Utc>, _>("updated_at"),
+            "category": category,
+            "retryable": failed_document_service::is_retryable(&failure_reason)
+                && row.get::<Option<chrono::DateTime<chrono::Utc>>, _>("requeued_at").is_none(),
             
             // Computed fields for backward compatibility
-            "failure_category": categorize_failure_reason(
-                Some(&row.get::<String, _>("failure_reason")),
-                row.get::<Option<String>, _>("error_message").as_deref()
-            ),
+            "failure_category": category.display_name(),
             "source": match row.get::<String, _>("failure_stage").as_str() {
                 "ocr" => "OCR Processing",
                 "ingestion" => "Document Ingestion", 
@@ -183,13 +227,16 @@ pub async fn get_failed_documents(
     // Calculate statistics for the response
     let mut stage_stats = HashMap::new();
     let mut reason_stats = HashMap::new();
+    let mut category_stats = HashMap::new();
     
     for doc in &documents {
         let stage = doc["failure_stage"].as_str().unwrap_or("unknown");
         let reason = doc["failure_reason"].as_str().unwrap_or("unknown");
+        let category = doc["category"].as_str().unwrap_or("other");
         
         *stage_stats.entry(stage).or_insert(0) += 1;
         *reason_stats.entry(reason).or_insert(0) += 1;
+        *category_stats.entry(category).or_insert(0) += 1;
     }
     
     let response = serde_json::json!({
@@ -203,11 +250,14 @@ pub async fn get_failed_documents(
         "statistics": {
             "total_failed": total_count,
             "by_stage": stage_stats,
-            "by_reason": reason_stats
+            "by_reason": reason_stats,
+            "by_category": category_stats
         },
         "filters": {
             "stage": params.stage,
-            "reason": params.reason
+            "reason": params.reason,
+            "category": params.category,
+            "include_requeued": params.include_requeued.unwrap_or(false)
         }
     });
     
@@ -324,7 +374,7 @@ pub async fn get_failed_ocr_documents(
                 "failure_category": categorize_failure_reason(
                     row.get::<Option<String>, _>("ocr_failure_reason").as_deref(),
                     row.get::<Option<String>, _>("ocr_error").as_deref()
-                )
+                ).display_name()
             })
         })
         .collect();
@@ -428,37 +478,157 @@ pub async fn view_failed_document(
     Ok(response)
 }
 
-/// Helper function to categorize failure reasons
-fn categorize_failure_reason(failure_reason: Option<&str>, error_message: Option<&str>) -> &'static str {
-    match failure_reason {
-        Some("pdf_font_encoding") => "PDF Font Issues",
-        Some("pdf_corruption") => "PDF Corruption", 
-        Some("processing_timeout") => "Timeout",
-        Some("memory_limit") => "Memory Limit",
-        Some("pdf_parsing_panic") => "PDF Parsing Error",
-        Some("low_ocr_confidence") => "Low OCR Confidence",
-        Some("unknown") | None => {
-            // Try to categorize based on error message
-            if let Some(error) = error_message {
-                let error_lower = error.to_lowercase();
-                if error_lower.contains("timeout") {
-                    "Timeout"
-                } else if error_lower.contains("memory") {
-                    "Memory Limit" 
-                } else if error_lower.contains("font") || error_lower.contains("encoding") {
-                    "PDF Font Issues"
-                } else if error_lower.contains("corrupt") {
-                    "PDF Corruption"
-                } else if error_lower.contains("quality below threshold") || error_lower.contains("confidence") {
-                    "Low OCR Confidence"
-                } else {
-                    "Unknown Error"
-                }
-            } else {
-                "Unknown Error"
+/// Every recorded failure of the same file as a failed document
+#[utoipa::path(
+    get,
+    path = "/api/documents/failed/{id}/history",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Failed Document ID")
+    ),
+    responses(
+        (status = 200, description = "Failures of the same file, oldest first", body = FailedDocumentHistory),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Failed document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_failed_document_history(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(failed_document_id): Path<uuid::Uuid>,
+) -> Result<Json<FailedDocumentHistory>, StatusCode> {
+    let failed = get_own_failed_document(&state, &auth_user, failed_document_id).await?;
+
+    let attempts = state.db.get_failed_document_history(&failed).await.map_err(|e| {
+        error!("Failed to fetch failure history of {}: {}", failed_document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let requeued = attempts
+        .iter()
+        .any(|attempt| attempt.id == failed.id && attempt.requeued_at.is_some());
+
+    Ok(Json(FailedDocumentHistory {
+        failed_document_id: failed.id,
+        filename: failed.filename,
+        retryable: failed_document_service::is_retryable(&failed.failure_reason) && !requeued,
+        attempts,
+    }))
+}
+
+/// Requeue a failed document once the problem behind it is fixed
+///
+/// OCR failures of a document that still exists queue it for OCR again. Files refused at
+/// ingestion go through ingestion again from the copy kept when they failed; if they fail
+/// again, that is recorded as a new failed document.
+#[utoipa::path(
+    post,
+    path = "/api/documents/failed/{id}/retry",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Failed Document ID")
+    ),
+    responses(
+        (status = 200, description = "Failed document requeued", body = FailedDocumentRequeue),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Failed document not found"),
+        (status = 409, description = "Already requeued, a duplicate or infected file, or the file was not kept"),
+        (status = 422, description = "The file failed ingestion again"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn retry_failed_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(failed_document_id): Path<uuid::Uuid>,
+) -> Result<Json<FailedDocumentRequeue>, StatusCode> {
+    let failed = get_own_failed_document(&state, &auth_user, failed_document_id).await?;
+
+    let service = FailedDocumentService::new(
+        state.db.clone(),
+        state.file_service.as_ref().clone(),
+        state.queue_service.clone(),
+    );
+    let requeue = service
+        .requeue(&failed, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| match e {
+            RequeueError::Conflict(reason) => {
+                warn!("Did not requeue failed document {}: {}", failed_document_id, reason);
+                StatusCode::CONFLICT
             }
-        }
-        _ => "Other"
+            RequeueError::FailedAgain(reason) => {
+                warn!("Requeued failed document {} failed again: {}", failed_document_id, reason);
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            RequeueError::Failed(e) => {
+                error!("Failed to requeue failed document {}: {}", failed_document_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::FAILED_DOCUMENT_REQUEUE, "failed_document", Some(failed.id)).details(
+            serde_json::json!({
+                "filename": failed.filename,
+                "failure_reason": failed.failure_reason,
+                "action": requeue.action,
+                "document_id": requeue.document_id,
+            }),
+        ),
+    )
+    .await;
+
+    Ok(Json(requeue))
+}
+
+/// A failed document of the user, or of anyone for admins
+async fn get_own_failed_document(
+    state: &AppState,
+    auth_user: &AuthUser,
+    failed_document_id: uuid::Uuid,
+) -> Result<crate::models::FailedDocument, StatusCode> {
+    let user_id = (auth_user.user.role != UserRole::Admin).then_some(auth_user.user.id);
+    state
+        .db
+        .get_failed_document(failed_document_id, user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch failed document {}: {}", failed_document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Category of a failure reason, falling back to the error message for unknown or
+/// missing reasons
+fn categorize_failure_reason(failure_reason: Option<&str>, error_message: Option<&str>) -> FailureCategory {
+    if let Some(category) = failure_reason.and_then(FailureCategory::from_reason) {
+        return category;
+    }
+
+    let Some(error) = error_message else {
+        return FailureCategory::Other;
+    };
+    let error_lower = error.to_lowercase();
+    if error_lower.contains("timeout") || error_lower.contains("memory") {
+        FailureCategory::ResourceLimit
+    } else if error_lower.contains("font") || error_lower.contains("encoding") || error_lower.contains("corrupt") {
+        FailureCategory::DamagedFile
+    } else if error_lower.contains("quality below threshold") || error_lower.contains("confidence") {
+        FailureCategory::LowOcrConfidence
+    } else {
+        FailureCategory::Other
     }
 }
 
@@ -500,7 +670,7 @@ async fn get_failure_statistics(
             
             serde_json::json!({
                 "reason": reason.clone().unwrap_or_else(|| "unknown".to_string()),
-                "display_name": categorize_failure_reason(reason.as_deref(), None),
+                "display_name": categorize_failure_reason(reason.as_deref(), None).display_name(),
                 "count": count
             })
         })
@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::{
+    auth::AuthUser,
+    models::{DocumentFormFields, FormTemplate},
+    services::form_extraction_service::FormExtractionService,
+    AppState,
+};
+
+/// Get the key-value pairs extracted from a form document
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/form-fields",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Extracted form fields", body = DocumentFormFields),
+        (status = 404, description = "Document not found or no form fields extracted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_form_fields(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<DocumentFormFields>, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let fields = state
+        .db
+        .get_document_form_fields(document_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load form fields for document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(fields))
+}
+
+/// Run (or re-run) form field extraction on a document
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/form-fields/extract",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Extracted form fields", body = DocumentFormFields),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Document has no text yet"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn extract_document_form_fields(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<DocumentFormFields>, StatusCode> {
+    let document = state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let has_text = document.ocr_text.as_deref().or(document.content.as_deref()).is_some_and(|t| !t.trim().is_empty());
+    if !has_text {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let fields = FormExtractionService::new(state.db.clone())
+        .extract_for_document(&document)
+        .await
+        .map_err(|e| {
+            warn!("Form extraction failed for document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(fields))
+}
+
+/// List the form layouts learned for the current user
+#[utoipa::path(
+    get,
+    path = "/api/documents/form-templates",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Learned form templates", body = Vec<FormTemplate>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_form_templates(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<FormTemplate>>, StatusCode> {
+    let templates = state.db.get_form_templates(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to load form templates: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(templates))
+}
+
+/// Forget a learned form layout
+#[utoipa::path(
+    delete,
+    path = "/api/documents/form-templates/{template_id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("template_id" = uuid::Uuid, Path, description = "Form template ID")
+    ),
+    responses(
+        (status = 204, description = "Template deleted"),
+        (status = 404, description = "Template not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_form_template(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(template_id): Path<uuid::Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_form_template(template_id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete form template {}: {}", template_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
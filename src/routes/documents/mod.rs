@@ -1,4 +1,4 @@
-use axum::{routing::{get, post, delete}, Router};
+use axum::{extract::DefaultBodyLimit, routing::{get, post, put, patch, delete}, Router};
 use std::sync::Arc;
 use crate::AppState;
 
@@ -8,6 +8,31 @@ pub mod ocr;
 pub mod bulk;
 pub mod debug;
 pub mod failed;
+pub mod artifacts;
+pub mod split;
+pub mod forms;
+pub mod pages;
+pub mod attachments;
+pub mod similar;
+pub mod versions;
+pub mod compare;
+pub mod search;
+pub mod related;
+pub mod archive;
+pub mod annotations;
+pub mod redaction;
+pub mod pii;
+pub mod security;
+pub mod signatures;
+pub mod translations;
+pub mod audio;
+pub mod progress;
+pub mod capture;
+pub mod ocr_words;
+pub mod relations;
+pub mod omr;
+pub mod titles;
+pub mod batch_upload;
 
 // Re-export commonly used types and functions for backward compatibility
 pub use types::*;
@@ -16,22 +41,135 @@ pub use ocr::*;
 pub use bulk::*;
 pub use debug::*;
 pub use failed::*;
+pub use artifacts::*;
+pub use split::*;
+pub use forms::*;
+pub use pages::*;
+pub use attachments::*;
+pub use similar::*;
+pub use versions::*;
+pub use compare::*;
+pub use search::*;
+pub use related::*;
+pub use archive::*;
+pub use annotations::*;
+pub use redaction::*;
+pub use pii::*;
+pub use security::*;
+pub use signatures::*;
+pub use translations::*;
+pub use audio::*;
+pub use progress::*;
+pub use capture::*;
+pub use ocr_words::*;
+pub use relations::*;
+pub use omr::*;
+pub use titles::*;
+pub use batch_upload::*;
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         // CRUD operations
         .route("/", post(upload_document))
         .route("/", get(list_documents))
+        .route("/capture", post(upload_capture))
+        .route(
+            "/bulk-upload",
+            post(batch_upload_documents).layer(DefaultBodyLimit::max(batch_upload_max_size_bytes())),
+        )
         .route("/{id}", get(get_document_by_id))
         .route("/{id}", delete(delete_document))
+        .route("/{id}/rename", put(rename_document))
+        .route(
+            "/{id}/title",
+            get(get_document_title).post(generate_document_title).delete(restore_document_title),
+        )
         .route("/{id}/download", get(download_document))
+        .route("/download-zip", post(download_documents_zip))
+        .route("/compare", get(compare_documents))
         .route("/{id}/view", get(view_document))
+        .route("/{id}/preview", get(preview_document))
+        .route("/{id}/versions", get(get_document_versions))
+        .route("/{id}/versions/diff", get(get_document_version_diff))
+        .route("/{id}/versions/{version}/restore", post(restore_document_version))
+        .route("/{id}/redact", post(redact_document))
+        .route("/{id}/pii", get(get_document_pii))
+        .route("/{id}/pii/scan", post(scan_document_pii))
+        .route("/{id}/security", get(get_document_security))
+        .route("/{id}/security/sanitize", post(sanitize_document))
+        .route("/{id}/signatures", get(get_document_signatures))
+        .route("/{id}/signatures/verify", post(verify_document_signatures))
+        .route("/{id}/translate", post(translate_document))
+        .route("/{id}/translations", get(list_document_translations))
+        .route(
+            "/{id}/translations/{language}",
+            get(get_document_translation).delete(delete_document_translation),
+        )
+        .route(
+            "/{id}/audio",
+            get(get_document_audio).post(narrate_document).delete(delete_document_audio),
+        )
+        .route("/{id}/audio/status", get(get_document_audio_status))
+        .route("/{id}/search", get(search_within_document))
+        .route("/{id}/similar", get(get_related_documents))
+        .route("/{id}/relations", get(list_document_relations).post(create_document_relation))
+        .route("/{id}/relations/suggestions", get(suggest_document_relations))
+        .route(
+            "/{id}/relations/{relation_id}",
+            put(update_document_relation).delete(delete_document_relation),
+        )
+        .route("/{id}/original", get(crate::routes::storage_compression::download_compressed_original))
+        .route("/{id}/correspondent", get(crate::routes::correspondents::get_document_correspondent))
+        .route("/{id}/timeline", get(crate::routes::timeline::get_document_timeline))
+        .route(
+            "/{id}/custom-fields",
+            get(crate::routes::document_types::get_document_custom_fields)
+                .put(crate::routes::document_types::set_document_custom_fields)
+                .delete(crate::routes::document_types::clear_document_custom_fields),
+        )
+        .route("/{id}/invoice/extract", post(crate::routes::invoices::extract_invoice))
+        .route(
+            "/{id}/expiration",
+            get(crate::routes::expirations::get_document_expiration)
+                .put(crate::routes::expirations::set_document_expiration)
+                .delete(crate::routes::expirations::delete_document_expiration),
+        )
+        .route("/{id}/expiration/extract", post(crate::routes::expirations::extract_document_expiration))
+        .route(
+            "/{id}/reminders",
+            get(crate::routes::reminders::list_document_reminders).post(crate::routes::reminders::create_document_reminder),
+        )
+        .route("/{id}/share", post(crate::routes::share_links::create_share_link))
+        .route("/{id}/share", get(crate::routes::share_links::list_document_share_links))
+
+        // Annotations and their comment threads
+        .route("/{id}/annotations", get(list_document_annotations).post(create_document_annotation))
+        .route(
+            "/{id}/annotations/{annotation_id}",
+            put(update_document_annotation).delete(delete_document_annotation),
+        )
+        .route("/{id}/annotations/{annotation_id}/comments", post(create_annotation_comment))
+        .route(
+            "/{id}/annotations/{annotation_id}/comments/{comment_id}",
+            put(update_annotation_comment).delete(delete_annotation_comment),
+        )
+        .route("/{id}/annotations/ws", get(annotation_changes_websocket))
+
+        // Processing progress as server-sent events
+        .route("/{id}/events", get(document_events))
         
         // OCR operations
         .route("/{id}/ocr", get(get_document_ocr))
+        .route("/{id}/ocr_text", patch(update_document_ocr_text))
         .route("/{id}/ocr/retry", post(retry_ocr))
         .route("/ocr/stats", get(get_ocr_stats))
         .route("/{id}/ocr/stop", post(cancel_ocr))
+        .route("/{id}/ocr/vision-pages", get(get_document_vision_pages))
+        .route("/{id}/ocr/region", post(ocr_document_region))
+        .route("/ocr/vision-quota", get(get_vision_quota))
+        .route("/{id}/ocr/low-confidence", get(get_low_confidence_words))
+        .route("/{id}/ocr/words/analyze", post(analyze_document_words))
+        .route("/ocr/low-confidence", get(list_low_confidence_documents))
         
         // OCR retry operations
         .route("/ocr/retry-stats", get(crate::routes::documents_ocr_retry::get_ocr_retry_stats))
@@ -40,6 +178,8 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/{id}/ocr/retry-history", get(crate::routes::documents_ocr_retry::get_document_retry_history))
         
         // Bulk operations
+        .route("/bulk", get(list_bulk_operations).post(create_bulk_operation))
+        .route("/bulk/{id}", get(get_bulk_operation))
         .route("/bulk/delete", post(bulk_delete_documents))
         .route("/cleanup/low-confidence", delete(delete_low_confidence_documents))
         .route("/cleanup/failed-ocr", delete(delete_failed_ocr_documents))
@@ -50,9 +190,45 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/{id}/processed", get(get_processed_image))
         .route("/{id}/validate", get(validate_document_integrity))
         .route("/duplicates", get(get_user_duplicates))
+        .route("/duplicates/similar", get(get_similar_documents))
+        .route("/duplicates/similar/scan", post(scan_similar_documents))
+        .route("/duplicates/similar/dismiss", post(dismiss_similar_documents))
+        .route("/duplicates/merge", post(merge_duplicate_documents))
         
         // Failed documents
         .route("/failed", get(get_failed_documents))
         .route("/failed/{id}", get(view_failed_document))
+        .route("/failed/{id}/history", get(get_failed_document_history))
+        .route("/failed/{id}/retry", post(retry_failed_document))
         .route("/failed/ocr", get(get_failed_ocr_documents))
+        
+        // Page artifacts (stamps, signatures, figures)
+        .route("/artifacts/search", get(search_page_artifacts))
+        .route("/{id}/artifacts", get(get_document_artifacts))
+        .route("/{id}/artifacts/extract", post(extract_document_artifacts))
+
+        // Batch scan splitting
+        .route("/{id}/split", post(split_document))
+        .route("/{id}/splits", get(get_document_splits))
+
+        // Form field extraction
+        .route("/{id}/form-fields", get(get_document_form_fields))
+        .route("/{id}/form-fields/extract", post(extract_document_form_fields))
+        .route("/form-templates", get(list_form_templates))
+        .route("/form-templates/{template_id}", delete(delete_form_template))
+
+        // Optical mark recognition with admin-defined templates
+        .route("/{id}/omr", get(get_document_omr))
+        .route("/{id}/omr/extract", post(extract_document_omr))
+
+        // Merging and page order
+        .route("/merge", post(merge_documents))
+        .route("/{id}/pages/reorder", post(reorder_document_pages))
+        .route("/{id}/pages/rotate", post(rotate_document_pages))
+        .route("/{id}/pages/transforms", get(get_document_page_transforms).delete(reset_document_page_transforms))
+        .route("/{id}/pages/{page}/image", get(get_document_page_image))
+        .route("/{id}/searchable-pdf", get(get_document_searchable_pdf))
+
+        // Email attachments
+        .route("/{id}/attachments", get(get_document_attachments))
 }
\ No newline at end of file
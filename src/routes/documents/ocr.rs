@@ -3,12 +3,22 @@ use axum::{
     http::StatusCode,
     response::Json as ResponseJson,
 };
+use serde_json::json;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
 use crate::{
     auth::AuthUser,
-    models::DocumentOcrResponse,
+    errors::panic::spawn_guarded,
+    models::{
+        DocumentOcrResponse, EventType, QueueKind, SharePermission, UpdateOcrTextRequest, LLM_FEATURE_GRAPH_EXTRACTION,
+    },
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        event_service::EventService,
+        llm::llm_service::LLMService,
+        maintenance_service, processing_usage,
+    },
     AppState,
 };
 
@@ -37,7 +47,7 @@ pub async fn get_document_ocr(
 ) -> Result<ResponseJson<DocumentOcrResponse>, StatusCode> {
     let document = state
         .db
-        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
         .await
         .map_err(|e| {
             error!("Database error getting document {}: {}", document_id, e);
@@ -45,19 +55,164 @@ pub async fn get_document_ocr(
         })?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    let response = DocumentOcrResponse {
+    let correction = state.db.get_ocr_text_correction(document_id).await.map_err(|e| {
+        error!("Database error getting OCR correction of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let raw = state.db.get_document_ocr_text_raw(document_id).await.map_err(|e| {
+        error!("Database error getting raw OCR text of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(ocr_response(document, raw, correction)))
+}
+
+fn ocr_response(
+    document: crate::models::Document,
+    raw: Option<String>,
+    correction: Option<(chrono::DateTime<chrono::Utc>, Option<uuid::Uuid>)>,
+) -> DocumentOcrResponse {
+    DocumentOcrResponse {
         id: document.id,
         filename: document.original_filename,
         has_ocr_text: document.ocr_text.is_some(),
         ocr_text: document.ocr_text,
+        ocr_text_raw: raw,
         ocr_confidence: document.ocr_confidence,
         ocr_status: document.ocr_status,
         ocr_processing_time_ms: document.ocr_processing_time_ms,
         detected_language: None, // This would need to be stored separately if needed
         pages_processed: None,   // This would need to be stored separately if needed
-    };
+        corrected_at: correction.map(|(at, _)| at),
+        corrected_by: correction.and_then(|(_, by)| by),
+    }
+}
+
+/// Correct OCR mistakes in a document's text, all of it or page by page. The corrected
+/// text is reindexed for search and marked as corrected by hand until OCR runs again.
+#[utoipa::path(
+    patch,
+    path = "/api/documents/{id}/ocr_text",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    request_body = UpdateOcrTextRequest,
+    responses(
+        (status = 200, description = "Corrected OCR text", body = DocumentOcrResponse),
+        (status = 400, description = "Neither or both of text and pages, or a page the text doesn't have"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 409, description = "The text changed while the correction was made"),
+        (status = 423, description = "The document is on legal hold"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_document_ocr_text(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(document_id): Path<uuid::Uuid>,
+    Json(request): Json<UpdateOcrTextRequest>,
+) -> Result<ResponseJson<DocumentOcrResponse>, StatusCode> {
+    if let Err(e) = request.validate() {
+        debug!("Invalid OCR text correction: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Edit)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+
+    let text = request.apply(document.ocr_text.as_deref()).map_err(|e| {
+        debug!("Invalid OCR text correction of document {}: {}", document_id, e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let corrected_at = state
+        .db
+        .correct_document_ocr_text(document_id, document.ocr_text.as_deref(), &text, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to correct OCR text of document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    // Words of corrected pages are no longer uncertain
+    let corrected_pages: Option<Vec<i32>> =
+        request.pages.as_ref().map(|pages| pages.iter().map(|edit| edit.page as i32).collect());
+    if let Err(e) = state.db.delete_ocr_page_words(document_id, corrected_pages.as_deref()).await {
+        warn!("Failed to clear word confidences of document {}: {}", document_id, e);
+    }
+
+    let previous_length = document.ocr_text.as_deref().map_or(0, |text| text.chars().count());
+    document.ocr_text = Some(text);
+
+    // The search vector follows the text in the database; external search engines
+    // reindex on the event
+    EventService::new(state.db.clone())
+        .publish_document_updated(&document, &["ocr_text"])
+        .await;
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_OCR_TEXT_CORRECT, "document", Some(document_id)).details(json!({
+            "pages": request.pages.as_ref().map(|pages| pages.iter().map(|edit| edit.page).collect::<Vec<_>>()),
+            "previous_length": previous_length,
+            "length": document.ocr_text.as_deref().map_or(0, |text| text.chars().count()),
+        })),
+    )
+    .await;
+
+    if request.reanalyze && maintenance_service::holds(QueueKind::Analysis) {
+        info!("Analysis is paused, not re-analyzing corrected document {}", document_id);
+    } else if request.reanalyze {
+        let db = state.db.clone();
+        let user_id = auth_user.user.id;
+        spawn_guarded("Re-analyze corrected document", async move {
+            let llm = LLMService::new(db.get_pool().clone());
+            let graph = match processing_usage::for_document(document_id, llm.analyze_document(document_id)).await {
+                Ok(graph) => graph,
+                Err(e) => {
+                    warn!("Failed to re-analyze corrected document {}: {}", document_id, e);
+                    return;
+                }
+            };
+            if llm.chat_enabled() && !graph.cached {
+                if let Err(e) = db.record_llm_usage(user_id, LLM_FEATURE_GRAPH_EXTRACTION, 1, Some(document_id)).await {
+                    warn!("Failed to record LLM usage of document {}: {}", document_id, e);
+                }
+            }
+            EventService::new(db)
+                .publish_best_effort(
+                    EventType::AnalysisCompleted,
+                    Some(user_id),
+                    Some(document_id),
+                    json!({
+                        "document_id": document_id,
+                        "node_count": graph.nodes.len(),
+                        "edge_count": graph.edges.len(),
+                    }),
+                )
+                .await;
+        });
+    }
 
-    Ok(ResponseJson(response))
+    info!("User {} corrected the OCR text of document {}", auth_user.user.id, document_id);
+    // The corrected text no longer comes from the raw OCR text
+    Ok(ResponseJson(ocr_response(document, None, Some((corrected_at, Some(auth_user.user.id))))))
 }
 
 /// Retry OCR processing for a document
@@ -301,4 +456,188 @@ pub async fn update_ocr_settings(
         "success": true,
         "message": "OCR settings updated"
     })))
-}
\ No newline at end of file
+}
+/// List pages of a document that were transcribed by the vision model
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/ocr/vision-pages",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Pages that used the vision LLM fallback", body = Vec<crate::models::VisionFallbackPage>),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_vision_pages(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<ResponseJson<Vec<crate::models::VisionFallbackPage>>, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let pages = state
+        .db
+        .get_vision_fallback_pages(document_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load vision fallback pages for document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(ResponseJson(pages))
+}
+
+/// Get the current user's daily vision transcription quota
+#[utoipa::path(
+    get,
+    path = "/api/documents/ocr/vision-quota",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Vision fallback pages used and remaining today", body = crate::models::LlmQuotaStatus),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_vision_quota(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<ResponseJson<crate::models::LlmQuotaStatus>, StatusCode> {
+    let service = crate::services::vision_fallback_service::VisionFallbackService::new(
+        state.db.clone(),
+        state.file_service.as_ref().clone(),
+    );
+
+    let status = service.quota_status(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to load vision quota for user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(status))
+}
+
+/// OCR a rectangle of one page at high resolution and return the text immediately
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/ocr/region",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    request_body = super::types::RegionOcrRequest,
+    responses(
+        (status = 200, description = "Text recognized in the region", body = super::types::RegionOcrResponse),
+        (status = 400, description = "Invalid region, page or language"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 422, description = "Document is not a PDF or image"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Server was built without OCR support")
+    )
+)]
+pub async fn ocr_document_region(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+    Json(request): Json<super::types::RegionOcrRequest>,
+) -> Result<ResponseJson<super::types::RegionOcrResponse>, StatusCode> {
+    use crate::services::region_ocr_service::{region_dpi, PageRegion, RegionOcrService};
+
+    if !cfg!(feature = "ocr") {
+        return Err(StatusCode::NOT_IMPLEMENTED);
+    }
+
+    let region = PageRegion {
+        x: request.x,
+        y: request.y,
+        width: request.width,
+        height: request.height,
+    };
+    if request.page == 0 || !region.is_valid() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let document = state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !document.mime_type.starts_with("image/") && document.mime_type != "application/pdf" {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let language = match request.language {
+        Some(lang) => {
+            if let Err(e) = crate::ocr::health::OcrHealthChecker::new().validate_language(&lang) {
+                warn!("Invalid OCR language '{}' for region OCR: {}", lang, e);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            lang
+        }
+        None => state
+            .db
+            .get_user_settings(auth_user.user.id)
+            .await
+            .ok()
+            .flatten()
+            .map(|settings| settings.ocr_language)
+            .unwrap_or_else(|| "eng".to_string()),
+    };
+
+    let dpi = region_dpi(request.dpi);
+    let service = RegionOcrService::new(state.file_service.as_ref().clone());
+    let text = service
+        .ocr_region(&document, request.page, region, dpi, &language)
+        .await
+        .map_err(|e| {
+            error!("Region OCR failed for document {} page {}: {}", document_id, request.page, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let appended = request.append && !text.is_empty();
+    if appended {
+        state
+            .db
+            .append_document_ocr_text(document_id, &text)
+            .await
+            .map_err(|e| {
+                error!("Failed to append region text to document {}: {}", document_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        info!("Appended {} characters of region OCR to document {}", text.len(), document_id);
+    }
+
+    Ok(ResponseJson(super::types::RegionOcrResponse {
+        text,
+        page: request.page,
+        dpi,
+        language,
+        appended,
+    }))
+}
@@ -0,0 +1,174 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        Document, LowConfidenceDocument, LowConfidenceDocumentsQuery, LowConfidenceWordsQuery,
+        LowConfidenceWordsResponse,
+    },
+    services::ocr_word_service::OcrWordService,
+    AppState,
+};
+
+/// The requested threshold, or the configured one
+fn threshold(requested: Option<f32>) -> Result<f32, StatusCode> {
+    match requested {
+        Some(threshold) if (0.0..=100.0).contains(&threshold) => Ok(threshold),
+        Some(_) => Err(StatusCode::BAD_REQUEST),
+        None => Ok(OcrWordService::default_threshold()),
+    }
+}
+
+async fn visible_document(state: &AppState, auth_user: &AuthUser, document_id: uuid::Uuid) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn low_confidence_words(
+    state: &AppState,
+    document_id: uuid::Uuid,
+    threshold: f32,
+) -> Result<LowConfidenceWordsResponse, StatusCode> {
+    let pages = state.db.get_ocr_page_words(document_id).await.map_err(|e| {
+        error!("Failed to load word confidences of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(LowConfidenceWordsResponse::new(document_id, threshold, &pages))
+}
+
+/// List the words OCR was least sure of, to mark them on the pages
+///
+/// Words are listed least certain first, each with its page and pixel box on the page
+/// as rendered for analysis. Pages whose text was corrected by hand are left out.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/ocr/low-confidence",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        LowConfidenceWordsQuery
+    ),
+    responses(
+        (status = 200, description = "Words below the confidence threshold", body = LowConfidenceWordsResponse),
+        (status = 400, description = "Threshold outside 0 to 100"),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_low_confidence_words(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+    Query(query): Query<LowConfidenceWordsQuery>,
+) -> Result<Json<LowConfidenceWordsResponse>, StatusCode> {
+    let threshold = threshold(query.threshold)?;
+    visible_document(&state, &auth_user, document_id).await?;
+
+    Ok(Json(low_confidence_words(&state, document_id, threshold).await?))
+}
+
+/// Run (or re-run) word confidence analysis on a document
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/ocr/words/analyze",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        LowConfidenceWordsQuery
+    ),
+    responses(
+        (status = 200, description = "Words below the confidence threshold after analysis", body = LowConfidenceWordsResponse),
+        (status = 400, description = "Threshold outside 0 to 100"),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Document type cannot be rasterized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn analyze_document_words(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+    Query(query): Query<LowConfidenceWordsQuery>,
+) -> Result<Json<LowConfidenceWordsResponse>, StatusCode> {
+    let threshold = threshold(query.threshold)?;
+    let document = visible_document(&state, &auth_user, document_id).await?;
+    if !document.mime_type.starts_with("image/") && document.mime_type != "application/pdf" {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    // Read in the language the owner's documents are OCRed in
+    let language = state
+        .db
+        .get_user_settings(document.user_id)
+        .await
+        .ok()
+        .flatten()
+        .map(|settings| settings.ocr_language)
+        .unwrap_or_else(|| "eng".to_string());
+    let service = OcrWordService::new(state.db.clone(), state.file_service.as_ref().clone());
+    service.analyze_document(&document, &language).await.map_err(|e| {
+        error!("Word confidence analysis failed for document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(low_confidence_words(&state, document_id, threshold).await?))
+}
+
+/// Documents with the most uncertain words, to correct first
+#[utoipa::path(
+    get,
+    path = "/api/documents/ocr/low-confidence",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(LowConfidenceDocumentsQuery),
+    responses(
+        (status = 200, description = "Documents with words below the confidence threshold", body = Vec<LowConfidenceDocument>),
+        (status = 400, description = "Threshold outside 0 to 100"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_low_confidence_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<LowConfidenceDocumentsQuery>,
+) -> Result<Json<Vec<LowConfidenceDocument>>, StatusCode> {
+    let threshold = threshold(query.threshold)?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let documents = state
+        .db
+        .get_low_confidence_documents(auth_user.user.id, auth_user.user.role, threshold, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to list documents with low-confidence words: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(documents))
+}
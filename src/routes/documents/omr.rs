@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    auth::AuthUser,
+    models::{OmrExtractRequest, OmrResult},
+    services::omr_service::OmrService,
+    AppState,
+};
+
+/// Get the marks and fields read from a scanned form
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/omr",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Data read with the matching form template", body = OmrResult),
+        (status = 404, description = "Document not found or not read as a form"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_omr(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<OmrResult>, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let result = state
+        .db
+        .get_omr_result(document_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load form data of document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(result))
+}
+
+/// Read (or re-read) a scanned form, with the given template or the one matching its text
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/omr/extract",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    request_body = OmrExtractRequest,
+    responses(
+        (status = 200, description = "Data read from the form", body = OmrResult),
+        (status = 404, description = "Document or template not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "No template matches, or the document has no page to read it on"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn extract_document_omr(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+    Json(request): Json<OmrExtractRequest>,
+) -> Result<Json<OmrResult>, StatusCode> {
+    let document = state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if !document.mime_type.starts_with("image/") && document.mime_type != "application/pdf" {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let service = OmrService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let template = match request.template_id {
+        Some(template_id) => state
+            .db
+            .get_omr_template(template_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get form template {}: {}", template_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?,
+        None => service
+            .detect(&document)
+            .await
+            .map_err(|e| {
+                error!("Failed to match document {} against form templates: {}", document_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?,
+    };
+
+    // Read fields in the language the owner's documents are OCRed in
+    let language = state
+        .db
+        .get_user_settings(document.user_id)
+        .await
+        .ok()
+        .flatten()
+        .map(|settings| settings.ocr_language)
+        .unwrap_or_else(|| "eng".to_string());
+    let result = service
+        .extract(&document, &template, &language)
+        .await
+        .map_err(|e| {
+            error!("Mark recognition failed for document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    Ok(Json(result))
+}
@@ -0,0 +1,523 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{Json, Response},
+};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        Document, DocumentResponse, EventType, PageImageQuery, PageTransform, RotatePagesRequest, SharePermission,
+        UserRole,
+    },
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        document_pages_service::{validate_page_order, DocumentPagesService},
+        event_service::EventService,
+        page_transform_service::{self, PageTransformService},
+    },
+    AppState,
+};
+use super::types::{MergeDocumentsRequest, ReorderPagesRequest};
+
+/// Combine several PDF scans into a single new document
+#[utoipa::path(
+    post,
+    path = "/api/documents/merge",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = MergeDocumentsRequest,
+    responses(
+        (status = 200, description = "Merged document, queued for OCR", body = DocumentResponse),
+        (status = 400, description = "Fewer than two documents given"),
+        (status = 404, description = "A document was not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "A document is not a PDF"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn merge_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<MergeDocumentsRequest>,
+) -> Result<Json<DocumentResponse>, StatusCode> {
+    if request.document_ids.len() < 2 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut documents = Vec::with_capacity(request.document_ids.len());
+    for document_id in &request.document_ids {
+        let document = state
+            .db
+            .get_document_by_id(*document_id, auth_user.user.id, auth_user.user.role)
+            .await
+            .map_err(|e| {
+                error!("Database error getting document {}: {}", document_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        if document.mime_type != "application/pdf" {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        documents.push(document);
+    }
+
+    let merged = merge_documents_as(
+        &state,
+        &documents,
+        auth_user.user.id,
+        auth_user.user.role,
+        request.filename.as_deref(),
+        request.delete_originals,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to merge documents: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(merged.into()))
+}
+
+/// Merge PDF documents into a new document of `user_id`, queue it for OCR and, with
+/// `delete_originals`, delete the sources. For callers without a request, such as
+/// bulk operations.
+pub(crate) async fn merge_documents_as(
+    state: &AppState,
+    documents: &[Document],
+    user_id: Uuid,
+    user_role: UserRole,
+    filename: Option<&str>,
+    delete_originals: bool,
+) -> anyhow::Result<Document> {
+    let service = DocumentPagesService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let merged = service.merge(user_id, documents, filename).await?;
+
+    let priority = 5; // Same priority as direct uploads
+    if let Err(e) = state.queue_service.enqueue_document(merged.id, priority, merged.file_size).await {
+        error!("Failed to enqueue merged document {} for OCR: {}", merged.id, e);
+    }
+
+    let merged_from: Vec<Uuid> = documents.iter().map(|document| document.id).collect();
+    let event_service = EventService::new(state.db.clone());
+    event_service
+        .publish_best_effort(
+            EventType::DocumentCreated,
+            Some(user_id),
+            Some(merged.id),
+            serde_json::json!({
+                "document_id": merged.id,
+                "filename": merged.filename,
+                "mime_type": merged.mime_type,
+                "file_size": merged.file_size,
+                "source_type": merged.source_type,
+                "merged_from": merged_from,
+            }),
+        )
+        .await;
+
+    if delete_originals {
+        for document in documents {
+            match state.db.delete_document(document.id, user_id, user_role).await {
+                Ok(true) => {
+                    if let Err(e) = state.file_service.delete_document_files(document).await {
+                        warn!("Failed to delete files for merged document {}: {}", document.id, e);
+                    }
+                    event_service
+                        .publish_best_effort(
+                            EventType::DocumentDeleted,
+                            Some(document.user_id),
+                            Some(document.id),
+                            serde_json::json!({
+                                "document_id": document.id,
+                                "filename": document.filename,
+                            }),
+                        )
+                        .await;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Failed to delete merged source document {}: {}", document.id, e),
+            }
+        }
+    }
+
+    info!("Merged {} documents into {}", documents.len(), merged.id);
+    Ok(merged)
+}
+
+/// Fix the page order of a PDF document
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/pages/reorder",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    request_body = ReorderPagesRequest,
+    responses(
+        (status = 200, description = "Document with reordered pages, queued for OCR", body = DocumentResponse),
+        (status = 400, description = "Page order is not a permutation of the document's pages"),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Document is not a PDF"),
+        (status = 423, description = "The document is on legal hold"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn reorder_document_pages(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+    Json(request): Json<ReorderPagesRequest>,
+) -> Result<Json<DocumentResponse>, StatusCode> {
+    let document = state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+
+    if document.mime_type != "application/pdf" {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let service = DocumentPagesService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let page_count = service.page_count(&document).await.map_err(|e| {
+        error!("Failed to count pages of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if let Err(e) = validate_page_order(&request.page_order, page_count) {
+        warn!("Invalid page order for document {}: {}", document_id, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let updated = service.reorder(&document, &request.page_order).await.map_err(|e| {
+        error!("Failed to reorder pages of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let priority = 5; // Same priority as direct uploads
+    if let Err(e) = state.queue_service.enqueue_document(updated.id, priority, updated.file_size).await {
+        error!("Failed to enqueue reordered document {} for OCR: {}", updated.id, e);
+    }
+
+    Ok(Json(updated.into()))
+}
+
+/// How each page of a document is turned upright, detected or set by hand. Pages
+/// without a transform are shown as they are stored.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/pages/transforms",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Page transforms by page number", body = [PageTransform]),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_page_transforms(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<Vec<PageTransform>>, StatusCode> {
+    let document = get_document(&state, &auth_user, document_id, SharePermission::View).await?;
+
+    let transforms = state.db.get_page_transforms(document.id).await.map_err(|e| {
+        error!("Failed to get page transforms of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(transforms))
+}
+
+/// Rotate pages of a document by quarter turns. The stored file is unchanged: the
+/// rotation is recorded as the pages' transform, which previews, thumbnails, OCR and
+/// searchable PDFs apply, and replaces any detected rotation.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/pages/rotate",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    request_body = RotatePagesRequest,
+    responses(
+        (status = 200, description = "New transforms of the rotated pages; the document is queued for OCR", body = [PageTransform]),
+        (status = 400, description = "Rotation is not a quarter turn, or a page does not exist"),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Document is not a PDF or image"),
+        (status = 423, description = "The document is on legal hold"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn rotate_document_pages(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(document_id): Path<uuid::Uuid>,
+    Json(request): Json<RotatePagesRequest>,
+) -> Result<Json<Vec<PageTransform>>, StatusCode> {
+    let document = get_document(&state, &auth_user, document_id, SharePermission::Edit).await?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+
+    if !page_transform_service::supports(&document.mime_type) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let service = PageTransformService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let page_count = service.page_count(&document).await.map_err(|e| {
+        error!("Failed to count pages of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if let Err(e) = request.pages(page_count) {
+        debug!("Rejected rotation of document {}: {}", document_id, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let transforms = service.rotate(&document, &request, auth_user.user.id).await.map_err(|e| {
+        error!("Failed to rotate pages of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let priority = 5; // Same priority as direct uploads
+    if let Err(e) = state.queue_service.enqueue_document(document.id, priority, document.file_size).await {
+        error!("Failed to enqueue rotated document {} for OCR: {}", document_id, e);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_PAGES_ROTATE, "document", Some(document_id)).details(serde_json::json!({
+            "pages": transforms.iter().map(|t| t.page_number).collect::<Vec<_>>(),
+            "rotation": request.rotation,
+            "reset_skew": request.reset_skew,
+        })),
+    )
+    .await;
+
+    Ok(Json(transforms))
+}
+
+/// Forget the page transforms of a document, detected or set by hand, and OCR it
+/// again, detecting them anew when orientation detection is on
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}/pages/transforms",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 204, description = "Transforms removed; the document is queued for OCR"),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 423, description = "The document is on legal hold"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn reset_document_page_transforms(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let document = get_document(&state, &auth_user, document_id, SharePermission::Edit).await?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+
+    let service = PageTransformService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let removed = service.reset(&document).await.map_err(|e| {
+        error!("Failed to reset page transforms of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if removed > 0 {
+        let priority = 5; // Same priority as direct uploads
+        if let Err(e) = state.queue_service.enqueue_document(document.id, priority, document.file_size).await {
+            error!("Failed to enqueue document {} for OCR: {}", document_id, e);
+        }
+        audit_service::record(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::DOCUMENT_PAGES_ROTATE, "document", Some(document_id))
+                .details(serde_json::json!({ "reset": true, "pages": removed })),
+        )
+        .await;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A page of a document as an image, turned upright as OCR sees it
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/pages/{page}/image",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("page" = u32, Path, description = "1-based page number"),
+        PageImageQuery
+    ),
+    responses(
+        (status = 200, description = "The page", content_type = "image/png"),
+        (status = 404, description = "Document or page not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Document is not a PDF or image"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_page_image(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((document_id, page)): Path<(uuid::Uuid, u32)>,
+    Query(query): Query<PageImageQuery>,
+) -> Result<Response<Body>, StatusCode> {
+    let document = get_document(&state, &auth_user, document_id, SharePermission::View).await?;
+    if !page_transform_service::supports(&document.mime_type) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let service = PageTransformService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let page_count = service.page_count(&document).await.map_err(|e| {
+        error!("Failed to count pages of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if page == 0 || page > page_count {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let png = service.render_page(&document, page, query.dpi).await.map_err(|e| {
+        error!("Failed to render page {} of document {}: {}", page, document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "image/png")
+        .header(header::CONTENT_LENGTH, png.len().to_string())
+        .body(Body::from(png))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// The document as a PDF with its pages turned upright and a text layer from OCR,
+/// generated on request; the stored file is unchanged
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/searchable-pdf",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Searchable PDF of the document", content_type = "application/pdf"),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Document is not a PDF or image"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_searchable_pdf(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Response<Body>, StatusCode> {
+    let document = get_document(&state, &auth_user, document_id, SharePermission::View).await?;
+    if !page_transform_service::supports(&document.mime_type) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    // The text layer is recognized in the language the document was OCRed with
+    let language = state
+        .db
+        .get_user_settings(document.user_id)
+        .await
+        .ok()
+        .flatten()
+        .map(|settings| settings.ocr_language)
+        .unwrap_or_else(|| "eng".to_string());
+
+    let service = PageTransformService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let pdf = service.searchable_pdf(&document, &language).await.map_err(|e| {
+        error!("Failed to generate searchable PDF of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_DOWNLOAD, "document", Some(document.id))
+            .details(serde_json::json!({ "searchable_pdf": true })),
+    )
+    .await;
+
+    let stem = std::path::Path::new(&document.original_filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "document".to_string());
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}_searchable.pdf\"", stem))
+        .header(header::CONTENT_LENGTH, pdf.len().to_string())
+        .body(Body::from(pdf))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+async fn get_document(
+    state: &AppState,
+    auth_user: &AuthUser,
+    document_id: Uuid,
+    permission: SharePermission,
+) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, permission)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
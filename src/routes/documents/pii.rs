@@ -0,0 +1,117 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{DocumentPiiResponse, PiiScanQuery, SharePermission},
+    services::pii_service::PiiService,
+    AppState,
+};
+
+/// Personal data found in the document's OCR text, with a redaction request that would
+/// black it out. Documents are scanned after OCR when `PII_SCAN_ENABLED` is set, or on
+/// request.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/pii",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Findings of the last scan; scanned_at is null when the document was never scanned", body = DocumentPiiResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_pii(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<DocumentPiiResponse>, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let response = PiiService::new(state.db.clone())
+        .findings_response(document_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get personal data findings of document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(response))
+}
+
+/// Scan the document's OCR text for personal data now, replacing earlier findings
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/pii/scan",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        PiiScanQuery
+    ),
+    responses(
+        (status = 200, description = "Findings of the new scan", body = DocumentPiiResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 409, description = "The document has no text yet"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn scan_document_pii(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Query(query): Query<PiiScanQuery>,
+) -> Result<Json<DocumentPiiResponse>, StatusCode> {
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Edit)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if !PiiService::has_text(&document) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let use_llm = query.use_llm.unwrap_or_else(PiiService::use_llm_by_default);
+    let response = PiiService::new(state.db.clone())
+        .scan(&document, use_llm)
+        .await
+        .map_err(|e| {
+            error!("Failed to scan document {} for personal data: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!(
+        "User {} scanned document {} for personal data: {} findings",
+        auth_user.user.id,
+        document_id,
+        response.findings.len()
+    );
+    Ok(Json(response))
+}
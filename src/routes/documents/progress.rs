@@ -0,0 +1,166 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::stream::Stream;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{Document, DocumentProgress, EventEnvelope, EventType, SharePermission, User},
+    services::{document_progress, event_service},
+    AppState,
+};
+
+/// How often an open stream re-checks that the user may still see the document
+const STREAM_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Events buffered for a client that reads slowly
+const STREAM_BUFFER: usize = 64;
+
+/// Events about a document that its stream passes on
+const STREAMED_EVENT_TYPES: [EventType; 6] = [
+    EventType::DocumentProgress,
+    EventType::DocumentUpdated,
+    EventType::OcrCompleted,
+    EventType::OcrFailed,
+    EventType::AnalysisCompleted,
+    EventType::DocumentDeleted,
+];
+
+/// Server-Sent Events stream of a document's processing progress
+///
+/// The first event is `document.progress` with where the document is now. Then every
+/// `document.progress`, `document.updated`, `ocr.completed`, `ocr.failed`,
+/// `analysis.completed` and `document.deleted` event of the document follows, with the
+/// event type as the SSE event name, the event id as its id and the event's `data` as
+/// JSON. The stream ends after `document.deleted` or when the user can no longer see
+/// the document. An alternative to the WebSocket event stream for clients and proxies
+/// that handle plain HTTP better.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/events",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Event stream of the document's progress", content_type = "text/event-stream", body = DocumentProgress),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn document_events(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    // Subscribe before taking the snapshot so nothing published in between is lost
+    let events = event_service::subscribe();
+    let document = load_document(&state, &auth_user.user, document_id).await?;
+
+    let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+    let first = progress_event(&document);
+    spawn_guarded(
+        format!("Event stream of document {}", document_id),
+        stream_document_events(state, auth_user.user, document_id, events, sender, first),
+    );
+
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|event| (Ok(event), receiver))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn load_document(state: &AppState, user: &User, document_id: Uuid) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, user.id, user.role, SharePermission::View)
+        .await
+        .map_err(|e| {
+            error!("Failed to get document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// `document.progress` with where the document is now
+fn progress_event(document: &Document) -> Event {
+    let progress = document_progress::current(document.id).unwrap_or_else(|| document_progress::from_document(document));
+    Event::default()
+        .event(EventType::DocumentProgress.as_str())
+        .json_data(&progress)
+        .unwrap_or_default()
+}
+
+fn envelope_event(envelope: &EventEnvelope) -> Event {
+    Event::default()
+        .event(envelope.event_type.as_str())
+        .id(envelope.event_id.to_string())
+        .json_data(&envelope.data)
+        .unwrap_or_default()
+}
+
+/// Pass the document's events to the client until it disconnects, the document is
+/// deleted or the user can no longer see it
+async fn stream_document_events(
+    state: Arc<AppState>,
+    user: User,
+    document_id: Uuid,
+    mut events: tokio::sync::broadcast::Receiver<EventEnvelope>,
+    sender: mpsc::Sender<Event>,
+    first: Event,
+) {
+    if sender.send(first).await.is_err() {
+        return;
+    }
+
+    let mut checks = tokio::time::interval(STREAM_CHECK_INTERVAL);
+    checks.tick().await;
+
+    loop {
+        tokio::select! {
+            received = events.recv() => match received {
+                Ok(envelope) => {
+                    if envelope.resource_id != Some(document_id) || !STREAMED_EVENT_TYPES.contains(&envelope.event_type) {
+                        continue;
+                    }
+                    if sender.send(envelope_event(&envelope)).await.is_err() {
+                        break;
+                    }
+                    if envelope.event_type == EventType::DocumentDeleted {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    // Progress is a state, so the latest one makes up for the missed events
+                    warn!("Event stream of document {} fell {} events behind", document_id, missed);
+                    let Ok(document) = load_document(&state, &user, document_id).await else {
+                        break;
+                    };
+                    if sender.send(progress_event(&document)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = sender.closed() => break,
+            _ = checks.tick() => {
+                if load_document(&state, &user, document_id).await.is_err() {
+                    debug!("Closing event stream of document {}: user {} lost access", document_id, user.id);
+                    break;
+                }
+            }
+        }
+    }
+}
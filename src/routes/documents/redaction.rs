@@ -0,0 +1,143 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{RedactDocumentRequest, RedactDocumentResponse, SharePermission},
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        event_service::EventService,
+        redaction_service::{self, RedactionService},
+    },
+    AppState,
+};
+
+/// Black out regions of pages and every occurrence of names or phrases. The redacted
+/// pages replace the document's contents, so the removed text is gone from the file
+/// and from the OCR text; the original is kept as a version only the owner and admins
+/// can see.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/redact",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    request_body = RedactDocumentRequest,
+    responses(
+        (status = 200, description = "Redacted document, queued for OCR", body = RedactDocumentResponse),
+        (status = 400, description = "No regions or terms, or a region outside the document's pages"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 422, description = "The document is not a PDF or image, or none of the terms was found"),
+        (status = 423, description = "The document is on legal hold"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn redact_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(document_id): Path<Uuid>,
+    Json(request): Json<RedactDocumentRequest>,
+) -> Result<Json<RedactDocumentResponse>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected redaction of document {}: {}", document_id, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Edit)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+
+    if !redaction_service::supports(&document.mime_type) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let service = RedactionService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let page_count = service.page_count(&document).await.map_err(|e| {
+        error!("Failed to count pages of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if request.regions.iter().any(|region| region.page > page_count) {
+        debug!("Rejected redaction of document {}: it has only {} pages", document_id, page_count);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Terms are found with the same OCR language the document was recognized with
+    let language = state
+        .db
+        .get_user_settings(document.user_id)
+        .await
+        .ok()
+        .flatten()
+        .map(|settings| settings.ocr_language)
+        .unwrap_or_else(|| "eng".to_string());
+
+    let outcome = service
+        .redact(&document, &request, &language)
+        .await
+        .map_err(|e| {
+            error!("Failed to redact document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let priority = 5; // Same priority as direct uploads
+    if let Err(e) = state
+        .queue_service
+        .enqueue_document(outcome.document.id, priority, outcome.document.file_size)
+        .await
+    {
+        error!("Failed to enqueue redacted document {} for OCR: {}", document_id, e);
+    }
+
+    EventService::new(state.db.clone())
+        .publish_document_updated(&outcome.document, &["file"])
+        .await;
+
+    // Only counts: the terms are what was meant to disappear
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_REDACT, "document", Some(document_id)).details(json!({
+            "original_version": outcome.original_version,
+            "regions": request.regions.len(),
+            "terms": request.terms.len(),
+            "term_matches": outcome.term_matches,
+            "pages_redacted": outcome.pages_redacted,
+        })),
+    )
+    .await;
+
+    info!(
+        "User {} redacted document {}; the original is version {}",
+        auth_user.user.id, document_id, outcome.original_version
+    );
+    Ok(Json(RedactDocumentResponse {
+        regions_redacted: request.regions.len(),
+        term_matches: outcome.term_matches,
+        pages_redacted: outcome.pages_redacted,
+        original_version: outcome.original_version,
+        redacted_by: auth_user.user.id,
+        document: outcome.document.into(),
+    }))
+}
@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{RelatedDocumentsQuery, RelatedDocumentsResponse},
+    AppState,
+};
+
+/// Distinctive terms of the source document the ranking uses
+const RELATED_TERMS: i64 = 20;
+
+/// List documents with text similar to a document's, e.g. related contracts or
+/// earlier invoices from the same vendor. Unlike `/api/documents/duplicates/similar`,
+/// which compares how pages look, this compares what the text says.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/similar",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        RelatedDocumentsQuery
+    ),
+    responses(
+        (status = 200, description = "Documents ranked by text similarity, most similar first", body = RelatedDocumentsResponse),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_related_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Query(query): Query<RelatedDocumentsQuery>,
+) -> Result<Json<RelatedDocumentsResponse>, StatusCode> {
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let terms = state.db.get_distinctive_terms(document_id, RELATED_TERMS).await.map_err(|e| {
+        error!("Failed to get the terms of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let documents = state
+        .db
+        .get_related_documents(document_id, &terms, auth_user.user.id, auth_user.user.role, limit)
+        .await
+        .map_err(|e| {
+            error!("Failed to find documents related to {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(RelatedDocumentsResponse { document_id, terms, documents }))
+}
@@ -0,0 +1,276 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        CreateDocumentRelationRequest, Document, DocumentRelation, DocumentRelationSuggestion,
+        LinkedDocumentRelation, RelationSuggestionsQuery, SharePermission, UpdateDocumentRelationRequest, User,
+        UserRole,
+    },
+    AppState,
+};
+
+/// Suggestions listed on the document detail response
+pub const DETAIL_RELATION_SUGGESTIONS: i64 = 5;
+/// Entities two graphs must share before their documents are suggested
+pub const DEFAULT_MIN_SHARED_ENTITIES: i64 = 2;
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+async fn load_document(
+    state: &AppState,
+    user: &User,
+    document_id: Uuid,
+    permission: SharePermission,
+) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, user.id, user.role, permission)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn load_relation(state: &AppState, document_id: Uuid, relation_id: Uuid) -> Result<DocumentRelation, StatusCode> {
+    state
+        .db
+        .get_document_relation(document_id, relation_id)
+        .await
+        .map_err(|e| internal_error("Failed to get document relation", e))?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Relations of a document to the documents the user can see
+pub async fn linked_relations(state: &AppState, user: &User, document_id: Uuid) -> Result<Vec<LinkedDocumentRelation>, StatusCode> {
+    state
+        .db
+        .get_linked_document_relations(document_id, user.id, user.role)
+        .await
+        .map_err(|e| internal_error("Failed to list document relations", e))
+}
+
+/// Relations the knowledge graph suggests for a document. Suggestions name other
+/// documents of its owner, so only the owner and admins get them.
+pub async fn relation_suggestions(
+    state: &AppState,
+    user: &User,
+    document: &Document,
+    min_shared_entities: i64,
+    limit: i64,
+) -> Result<Vec<DocumentRelationSuggestion>, StatusCode> {
+    if document.user_id != user.id && user.role != UserRole::Admin {
+        return Ok(Vec::new());
+    }
+    state
+        .db
+        .get_document_relation_suggestions(document.id, min_shared_entities, limit)
+        .await
+        .map_err(|e| internal_error("Failed to suggest document relations", e))
+}
+
+/// List a document's relations, in both directions
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/relations",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Relations to documents the user can see, newest first", body = Vec<LinkedDocumentRelation>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_document_relations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<Vec<LinkedDocumentRelation>>, StatusCode> {
+    load_document(&state, &auth_user.user, document_id, SharePermission::View).await?;
+
+    Ok(Json(linked_relations(&state, &auth_user.user, document_id).await?))
+}
+
+/// Relate a document to another one, e.g. a receipt as the `payment_for` an invoice.
+/// The document of the path is the relation's source.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/relations",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Source document ID")
+    ),
+    request_body = CreateDocumentRelationRequest,
+    responses(
+        (status = 201, description = "Relation created", body = DocumentRelation),
+        (status = 400, description = "Relation of a document to itself, or a note that is too long"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source document not found or not editable, or target document not found"),
+        (status = 409, description = "The documents already have a relation of this type"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_document_relation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Json(request): Json<CreateDocumentRelationRequest>,
+) -> Result<(StatusCode, Json<DocumentRelation>), StatusCode> {
+    if let Err(reason) = request.validate(document_id) {
+        debug!("Rejected document relation: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    load_document(&state, &auth_user.user, document_id, SharePermission::Edit).await?;
+    load_document(&state, &auth_user.user, request.target_document_id, SharePermission::View).await?;
+
+    let relation = state
+        .db
+        .create_document_relation(document_id, &request, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to create document relation", e))?
+        .ok_or(StatusCode::CONFLICT)?;
+    info!(
+        "User {} related document {} to {} ({})",
+        auth_user.user.id, document_id, request.target_document_id, relation.relation_type
+    );
+
+    Ok((StatusCode::CREATED, Json(relation)))
+}
+
+/// Change the type or note of a relation
+#[utoipa::path(
+    put,
+    path = "/api/documents/{id}/relations/{relation_id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "ID of either document of the relation"),
+        ("relation_id" = Uuid, Path, description = "Relation ID")
+    ),
+    request_body = UpdateDocumentRelationRequest,
+    responses(
+        (status = 200, description = "Relation updated", body = DocumentRelation),
+        (status = 400, description = "Note too long"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found or not editable, or relation not found"),
+        (status = 409, description = "The documents already have a relation of the new type"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_document_relation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((document_id, relation_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<UpdateDocumentRelationRequest>,
+) -> Result<Json<DocumentRelation>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected document relation change: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    load_document(&state, &auth_user.user, document_id, SharePermission::Edit).await?;
+    load_relation(&state, document_id, relation_id).await?;
+
+    let relation = state
+        .db
+        .update_document_relation(relation_id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to update document relation", e))?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    Ok(Json(relation))
+}
+
+/// Remove a relation
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}/relations/{relation_id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "ID of either document of the relation"),
+        ("relation_id" = Uuid, Path, description = "Relation ID")
+    ),
+    responses(
+        (status = 204, description = "Relation removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found or not editable, or relation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_document_relation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((document_id, relation_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    load_document(&state, &auth_user.user, document_id, SharePermission::Edit).await?;
+    load_relation(&state, document_id, relation_id).await?;
+
+    let deleted = state
+        .db
+        .delete_document_relation(relation_id)
+        .await
+        .map_err(|e| internal_error("Failed to delete document relation", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Documents of the same owner the knowledge graph suggests relating to, because both
+/// graphs name the same entities. Empty for documents shared with the user. A relationship like `PAYS` or `AMENDS` in the
+/// suggested document's graph gives the relation its type and direction; otherwise
+/// `related` is suggested.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/relations/suggestions",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        RelationSuggestionsQuery
+    ),
+    responses(
+        (status = 200, description = "Suggested relations, most shared entities first", body = Vec<DocumentRelationSuggestion>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn suggest_document_relations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Query(query): Query<RelationSuggestionsQuery>,
+) -> Result<Json<Vec<DocumentRelationSuggestion>>, StatusCode> {
+    let document = load_document(&state, &auth_user.user, document_id, SharePermission::View).await?;
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+    let min_shared = query.min_shared_entities.unwrap_or(DEFAULT_MIN_SHARED_ENTITIES).max(1);
+
+    Ok(Json(relation_suggestions(&state, &auth_user.user, &document, min_shared, limit).await?))
+}
@@ -0,0 +1,71 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{DocumentSearchQuery, DocumentSearchResponse},
+    AppState,
+};
+
+/// Same limit as `/api/search`
+const MAX_QUERY_LENGTH: usize = 1000;
+
+/// Find a phrase within one document, e.g. for find-in-document in the viewer
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/search",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        DocumentSearchQuery
+    ),
+    responses(
+        (status = 200, description = "Occurrences of the phrase in the document's OCR text, with page numbers and snippets", body = DocumentSearchResponse),
+        (status = 400, description = "Empty or too long query"),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn search_within_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Query(query): Query<DocumentSearchQuery>,
+) -> Result<Json<DocumentSearchResponse>, StatusCode> {
+    let phrase = query.q.trim();
+    if phrase.is_empty() || phrase.len() > MAX_QUERY_LENGTH {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000) as usize;
+    let offset = query.offset.unwrap_or(0).max(0) as usize;
+    let snippet_length = query.snippet_length.unwrap_or(120).clamp(20, 1000) as usize;
+
+    let document = state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let (total, matches) = state.db.find_in_document(&document, phrase, snippet_length, offset, limit);
+
+    Ok(Json(DocumentSearchResponse {
+        document_id,
+        query: phrase.to_string(),
+        total: total as i64,
+        matches,
+    }))
+}
@@ -0,0 +1,137 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{PdfSecurityReport, SharePermission},
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        event_service::EventService,
+        pdf_security_service::{self, PdfSecurityService},
+    },
+    AppState,
+};
+
+/// The active content found in a PDF when it was ingested: embedded JavaScript, launch
+/// actions, embedded files and the like, and whether it was stripped. PDFs without
+/// active content have no findings.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/security",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Security report; scanned_at is null when nothing was found or the document was never scanned", body = PdfSecurityReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_security(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<PdfSecurityReport>, StatusCode> {
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(pdf_security_service::stored_report(&document).unwrap_or_else(PdfSecurityReport::unscanned)))
+}
+
+/// Strip the active content from a PDF's current contents. The stripped copy replaces
+/// the contents and the current contents are kept as a version; a PDF without active
+/// content is left as it is.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/security/sanitize",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Report of the scan; sanitized_file_hash is set when active content was stripped and the document queued for OCR", body = PdfSecurityReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 422, description = "The document is not a PDF"),
+        (status = 423, description = "The document is on legal hold"),
+        (status = 500, description = "Internal server error, including active content that could not be stripped")
+    )
+)]
+pub async fn sanitize_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<PdfSecurityReport>, StatusCode> {
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Edit)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+
+    if document.mime_type != "application/pdf" {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let (sanitized, report) = PdfSecurityService::new(state.db.clone(), state.file_service.as_ref().clone())
+        .sanitize_document(&document)
+        .await
+        .map_err(|e| {
+            error!("Failed to strip the active content of document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if report.sanitized_file_hash.is_none() {
+        return Ok(Json(report));
+    }
+
+    let priority = 5; // Same priority as direct uploads
+    if let Err(e) = state.queue_service.enqueue_document(sanitized.id, priority, sanitized.file_size).await {
+        error!("Failed to enqueue sanitized document {} for OCR: {}", document_id, e);
+    }
+
+    EventService::new(state.db.clone())
+        .publish_document_updated(&sanitized, &["file"])
+        .await;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_SANITIZE, "document", Some(document_id)).details(json!({
+            "findings": report.findings,
+            "original_file_hash": report.file_hash,
+            "sanitized_file_hash": report.sanitized_file_hash,
+        })),
+    )
+    .await;
+
+    info!("User {} stripped the active content of document {}", auth_user.user.id, document_id);
+    Ok(Json(report))
+}
@@ -0,0 +1,101 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{PdfSignatureReport, SharePermission},
+    services::pdf_signature_service::{self, PdfSignatureService},
+    AppState,
+};
+
+/// The digital signatures of a PDF as last checked: who signed it and whether the
+/// signatures hold up. PDFs are checked at ingestion and again after their contents
+/// change; `status` is `broken` when a change in Readur invalidated them.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/signatures",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Signature report; checked_at is null when the document was never checked", body = PdfSignatureReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_signatures(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<PdfSignatureReport>, StatusCode> {
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(pdf_signature_service::stored_report(&document).unwrap_or_else(PdfSignatureReport::unchecked)))
+}
+
+/// Check the signatures of a PDF now and record the result
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/signatures/verify",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Signature report of the current contents", body = PdfSignatureReport),
+        (status = 400, description = "The document is not a PDF"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn verify_document_signatures(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<PdfSignatureReport>, StatusCode> {
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Edit)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if document.mime_type != "application/pdf" {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let report = PdfSignatureService::new(state.db.clone(), state.file_service.as_ref().clone())
+        .recheck(&document)
+        .await
+        .map_err(|e| {
+            error!("Failed to verify the signatures of document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(report))
+}
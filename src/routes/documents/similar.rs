@@ -0,0 +1,262 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{
+        DismissSimilarDocumentsRequest, Document, MergeDuplicatesRequest, MergeDuplicatesResponse,
+        SimilarDocument, SimilarDocumentGroup, SimilarDocumentsQuery, SimilarDocumentsResponse,
+    },
+    services::audit_service::{self, document_details, AuditEvent, ClientInfo},
+    services::similar_document_service::{SimilarDocumentService, DEFAULT_MAX_DISTANCE},
+    AppState,
+};
+use super::crud::remove_document;
+
+/// Most documents one dismiss or merge request may name
+const MAX_GROUP_SIZE: usize = 100;
+
+/// List groups of documents that look like scans of the same paper
+#[utoipa::path(
+    get,
+    path = "/api/documents/duplicates/similar",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(SimilarDocumentsQuery),
+    responses(
+        (status = 200, description = "Groups of similar documents, oldest document first", body = SimilarDocumentsResponse),
+        (status = 400, description = "Invalid distance"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_similar_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<SimilarDocumentsQuery>,
+) -> Result<Json<SimilarDocumentsResponse>, StatusCode> {
+    let max_distance = query.max_distance.unwrap_or(DEFAULT_MAX_DISTANCE);
+    if max_distance > 32 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.limit.unwrap_or(25).clamp(1, 100) as usize;
+    let offset = query.offset.unwrap_or(0).max(0) as usize;
+    let user_id = auth_user.user.id;
+
+    let service = SimilarDocumentService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let groups = service.find_groups(user_id, max_distance).await.map_err(|e| {
+        error!("Failed to find similar documents: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let total_groups = groups.len() as i64;
+    let groups: Vec<Vec<(Uuid, u32)>> = groups.into_iter().skip(offset).take(limit).collect();
+
+    let document_ids: Vec<Uuid> = groups.iter().flatten().map(|(id, _)| *id).collect();
+    let mut documents: HashMap<Uuid, Document> = state
+        .db
+        .get_documents_by_ids(&document_ids)
+        .await
+        .map_err(|e| {
+            error!("Failed to load similar documents: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .map(|document| (document.id, document))
+        .collect();
+    let unhashed_documents = state.db.count_unhashed_documents(user_id).await.map_err(|e| {
+        error!("Failed to count unhashed documents: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let groups = groups
+        .into_iter()
+        .map(|members| SimilarDocumentGroup {
+            documents: members
+                .into_iter()
+                .filter_map(|(id, distance)| {
+                    documents.remove(&id).map(|document| SimilarDocument {
+                        id: document.id,
+                        original_filename: document.original_filename,
+                        mime_type: document.mime_type,
+                        file_size: document.file_size,
+                        file_hash: document.file_hash,
+                        created_at: document.created_at,
+                        distance,
+                    })
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(SimilarDocumentsResponse { groups, total_groups, unhashed_documents }))
+}
+
+/// Hash the pages of PDFs and images that were added before perceptual hashing was
+/// enabled or whose hashing failed
+#[utoipa::path(
+    post,
+    path = "/api/documents/duplicates/similar/scan",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 202, description = "Hashing started in the background"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn scan_similar_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> StatusCode {
+    let user_id = auth_user.user.id;
+    let service = SimilarDocumentService::new(state.db.clone(), state.file_service.as_ref().clone());
+    spawn_guarded(format!("Page hashing for user {}", user_id), async move {
+        match service.hash_unhashed(user_id).await {
+            Ok(count) => info!("Hashed pages of {} documents for user {}", count, user_id),
+            Err(e) => error!("Page hashing failed for user {}: {}", user_id, e),
+        }
+    });
+
+    StatusCode::ACCEPTED
+}
+
+/// Mark documents as not being duplicates of each other, so they stop being grouped
+#[utoipa::path(
+    post,
+    path = "/api/documents/duplicates/similar/dismiss",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = DismissSimilarDocumentsRequest,
+    responses(
+        (status = 204, description = "Documents will no longer be grouped together"),
+        (status = 400, description = "Fewer than two or too many documents"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn dismiss_similar_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<DismissSimilarDocumentsRequest>,
+) -> Result<StatusCode, StatusCode> {
+    if request.document_ids.len() < 2 || request.document_ids.len() > MAX_GROUP_SIZE {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    state
+        .db
+        .dismiss_similar_documents(auth_user.user.id, &request.document_ids)
+        .await
+        .map_err(|e| {
+            error!("Failed to dismiss similar documents: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Keep one document of a duplicate group: the others' labels, tags and physical
+/// location are added to it, then they are deleted
+#[utoipa::path(
+    post,
+    path = "/api/documents/duplicates/merge",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = MergeDuplicatesRequest,
+    responses(
+        (status = 200, description = "Duplicates merged into the kept document", body = MergeDuplicatesResponse),
+        (status = 400, description = "No duplicates, too many, or documents of different owners"),
+        (status = 404, description = "A document was not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn merge_duplicate_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<MergeDuplicatesRequest>,
+) -> Result<Json<MergeDuplicatesResponse>, StatusCode> {
+    let mut duplicate_ids = request.duplicate_ids.clone();
+    duplicate_ids.sort();
+    duplicate_ids.dedup();
+    if duplicate_ids.is_empty() || duplicate_ids.len() > MAX_GROUP_SIZE || duplicate_ids.contains(&request.keep_id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let kept = load_document(&state, &auth_user, request.keep_id).await?;
+    let mut duplicates = Vec::with_capacity(duplicate_ids.len());
+    for document_id in &duplicate_ids {
+        let duplicate = load_document(&state, &auth_user, *document_id).await?;
+        // Admins may merge any user's documents, but not across users
+        if duplicate.user_id != kept.user_id {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        duplicates.push(duplicate);
+    }
+
+    let labels_added = state
+        .db
+        .merge_duplicate_metadata(kept.id, &duplicate_ids, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to merge metadata into document {}: {}", kept.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let mut deleted = 0;
+    for duplicate in &duplicates {
+        match remove_document(&state, duplicate, &auth_user).await {
+            Ok(true) => {
+                deleted += 1;
+                audit_service::record(
+                    &state.db,
+                    Some(&auth_user.user),
+                    &client,
+                    AuditEvent::new(audit_service::DOCUMENT_DELETE, "document", Some(duplicate.id))
+                        .details(serde_json::json!({ "merged_into": kept.id, "document": document_details(duplicate) })),
+                )
+                .await;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!("Failed to delete merged duplicate {}: {}", duplicate.id, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
+    info!("Merged {} duplicates into document {}", deleted, kept.id);
+    Ok(Json(MergeDuplicatesResponse {
+        kept_id: kept.id,
+        deleted,
+        labels_added: labels_added as i64,
+    }))
+}
+
+async fn load_document(state: &AppState, auth_user: &AuthUser, document_id: Uuid) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
@@ -0,0 +1,171 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::{
+    auth::AuthUser,
+    models::{DocumentSplit, EventType, SplitDocumentRequest, SplitDocumentResponse, SplitPart},
+    services::{document_split_service::DocumentSplitService, event_service::EventService},
+    AppState,
+};
+
+/// Split a batch-scanned PDF into the individual documents it contains
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/split",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    request_body = SplitDocumentRequest,
+    responses(
+        (status = 200, description = "Detected page ranges and the child documents created from them", body = SplitDocumentResponse),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Document has already been split"),
+        (status = 422, description = "Document is not a PDF or boundaries could not be detected"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn split_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+    Json(request): Json<SplitDocumentRequest>,
+) -> Result<Json<SplitDocumentResponse>, StatusCode> {
+    let document = state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if document.mime_type != "application/pdf" {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let existing = state.db.get_document_splits(document_id).await.map_err(|e| {
+        error!("Failed to load splits of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !existing.is_empty() && !request.dry_run {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let service = DocumentSplitService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let (page_count, ranges) = service
+        .detect_ranges(&document, request.method, request.separator_text.as_deref())
+        .await
+        .map_err(|e| {
+            warn!("Split detection failed for document {}: {}", document_id, e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+
+    // A single range means there is nothing to split
+    if request.dry_run || ranges.len() < 2 {
+        return Ok(Json(SplitDocumentResponse {
+            parent_document_id: document_id,
+            method: request.method,
+            page_count: page_count as i32,
+            parts: ranges
+                .into_iter()
+                .map(|(start, end)| SplitPart { start_page: start as i32, end_page: end as i32, document_id: None })
+                .collect(),
+        }));
+    }
+
+    let children = service
+        .create_children(&document, request.method, &ranges)
+        .await
+        .map_err(|e| {
+            error!("Failed to split document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let event_service = EventService::new(state.db.clone());
+    let mut parts = Vec::with_capacity(children.len());
+    for (start, end, child) in children {
+        let priority = 5; // Same priority as direct uploads
+        if let Err(e) = state.queue_service.enqueue_document(child.id, priority, child.file_size).await {
+            error!("Failed to enqueue split document {} for OCR: {}", child.id, e);
+        }
+
+        event_service
+            .publish_best_effort(
+                EventType::DocumentCreated,
+                Some(auth_user.user.id),
+                Some(child.id),
+                serde_json::json!({
+                    "document_id": child.id,
+                    "filename": child.filename,
+                    "mime_type": child.mime_type,
+                    "file_size": child.file_size,
+                    "source_type": child.source_type,
+                    "split_from": document_id,
+                }),
+            )
+            .await;
+
+        parts.push(SplitPart { start_page: start as i32, end_page: end as i32, document_id: Some(child.id) });
+    }
+
+    info!("Document {} split into {} documents", document_id, parts.len());
+
+    Ok(Json(SplitDocumentResponse {
+        parent_document_id: document_id,
+        method: request.method,
+        page_count: page_count as i32,
+        parts,
+    }))
+}
+
+/// List documents that were split out of a batch scan
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/splits",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Child documents with their page ranges", body = Vec<DocumentSplit>),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_splits(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<Vec<DocumentSplit>>, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let splits = state.db.get_document_splits(document_id).await.map_err(|e| {
+        error!("Failed to load splits of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(splits))
+}
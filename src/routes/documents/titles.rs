@@ -0,0 +1,174 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::{
+    auth::AuthUser,
+    models::{DocumentResponse, DocumentTitle, GenerateTitleQuery, GeneratedTitleResponse, SharePermission, TITLE_MODES},
+    services::{legal_hold_service, title_service::TitleService},
+    AppState,
+};
+
+async fn load_document(
+    state: &AppState,
+    auth_user: &AuthUser,
+    document_id: uuid::Uuid,
+    permission: SharePermission,
+) -> Result<crate::models::Document, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, permission)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Get the generated title of a document and the name it had before
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/title",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Generated title and original name", body = DocumentTitle),
+        (status = 404, description = "Document not found or never titled"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_title(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<DocumentTitle>, StatusCode> {
+    load_document(&state, &auth_user, document_id, SharePermission::View).await?;
+
+    let title = state
+        .db
+        .get_document_title(document_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load the title of document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(title))
+}
+
+/// Generate a title from the document's text and rename the document to it
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/title",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        GenerateTitleQuery
+    ),
+    responses(
+        (status = 200, description = "Generated title and the name it gives", body = GeneratedTitleResponse),
+        (status = 400, description = "Unknown mode, or `llm` without an LLM configured"),
+        (status = 404, description = "Document not found"),
+        (status = 422, description = "Document has no text, or no title was found in it"),
+        (status = 423, description = "Document is on legal hold"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn generate_document_title(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+    Query(query): Query<GenerateTitleQuery>,
+) -> Result<Json<GeneratedTitleResponse>, StatusCode> {
+    let mode = query.mode.as_deref().unwrap_or("auto");
+    if !TITLE_MODES.contains(&mode) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let service = TitleService::new(state.db.clone());
+    if mode == "llm" && !service.llm_available() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let document = load_document(&state, &auth_user, document_id, SharePermission::Edit).await?;
+    let has_text = document.ocr_text.as_deref().or(document.content.as_deref()).is_some_and(|t| !t.trim().is_empty());
+    if !has_text {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let dry_run = query.dry_run.unwrap_or(false);
+    if !dry_run {
+        crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+    }
+
+    let response = service
+        .generate(&document, mode, dry_run)
+        .await
+        .map_err(|e| {
+            if legal_hold_service::is_held_error(&e) {
+                return StatusCode::LOCKED;
+            }
+            warn!("Title generation failed for document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    Ok(Json(response))
+}
+
+/// Undo the generated title and give the document back its original name
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}/title",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Document with its original name", body = DocumentResponse),
+        (status = 404, description = "Document not found or never titled"),
+        (status = 423, description = "Document is on legal hold"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn restore_document_title(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<uuid::Uuid>,
+) -> Result<Json<DocumentResponse>, StatusCode> {
+    let document = load_document(&state, &auth_user, document_id, SharePermission::Edit).await?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+
+    let (title, document) = TitleService::new(state.db.clone())
+        .restore(&document)
+        .await
+        .map_err(|e| {
+            if legal_hold_service::is_held_error(&e) {
+                return StatusCode::LOCKED;
+            }
+            error!("Failed to restore the name of document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    info!("Document {} renamed back to '{}'", document_id, title.original_filename);
+    Ok(Json(DocumentResponse::from(document)))
+}
@@ -0,0 +1,236 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        normalize_language_code, DocumentTranslation, DocumentTranslationSummary, SharePermission, TranslateQuery,
+        TranslationProvider, LLM_FEATURE_TRANSLATION,
+    },
+    services::{
+        processing_usage,
+        translation_service::{TranslationService, MAX_TRANSLATION_CHARS},
+    },
+    AppState,
+};
+
+async fn load_document(
+    state: &AppState,
+    auth_user: &AuthUser,
+    id: Uuid,
+    permission: SharePermission,
+) -> Result<crate::models::Document, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(id, auth_user.user.id, auth_user.user.role, permission)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+fn language_code(language: &str) -> Result<String, StatusCode> {
+    normalize_language_code(language).ok_or_else(|| {
+        debug!("Invalid language code '{}'", language);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+/// Translate the document's text into a language and keep the translation, which is
+/// searchable with that language's rules. Translating again replaces it.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/translate",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        TranslateQuery
+    ),
+    responses(
+        (status = 200, description = "The translation", body = DocumentTranslation),
+        (status = 400, description = "Target is not an ISO 639-1 language code"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 422, description = "The document has no text, or more than can be translated"),
+        (status = 500, description = "Internal server error"),
+        (status = 502, description = "The translation API or LLM failed"),
+        (status = 503, description = "No translation API or LLM is configured")
+    )
+)]
+pub async fn translate_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(query): Query<TranslateQuery>,
+) -> Result<Json<DocumentTranslation>, StatusCode> {
+    let language = language_code(&query.target)?;
+    let document = load_document(&state, &auth_user, id, SharePermission::Edit).await?;
+
+    let service = TranslationService::new(&state.db);
+    if service.provider().is_none() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let text = TranslationService::document_text(&document).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    let source_chars = text.chars().count();
+    if source_chars > MAX_TRANSLATION_CHARS {
+        debug!("Document {} has {} characters, too many to translate", id, source_chars);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let translated = processing_usage::for_document(id, service.translate(text, &language)).await.map_err(|e| {
+        error!("Failed to translate document {} into {}: {}", id, language, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+
+    if translated.provider == TranslationProvider::Llm {
+        if let Err(e) = state
+            .db
+            .record_llm_usage(auth_user.user.id, LLM_FEATURE_TRANSLATION, translated.requests as i32, Some(id))
+            .await
+        {
+            warn!("Failed to record LLM usage of document {}: {}", id, e);
+        }
+    }
+
+    let translation = state
+        .db
+        .save_document_translation(
+            id,
+            &language,
+            &translated.text,
+            translated.provider,
+            translated.model.as_deref(),
+            source_chars as i32,
+            auth_user.user.id,
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to save translation of document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Translated document {} into {} with {} requests", id, language, translated.requests);
+    Ok(Json(translation))
+}
+
+/// The languages the document has been translated into
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/translations",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Translations without their text", body = Vec<DocumentTranslationSummary>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_document_translations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DocumentTranslationSummary>>, StatusCode> {
+    load_document(&state, &auth_user, id, SharePermission::View).await?;
+
+    let translations = state.db.list_document_translations(id).await.map_err(|e| {
+        error!("Failed to list translations of document {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(translations))
+}
+
+/// The document's translation into a language
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/translations/{language}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        ("language" = String, Path, description = "ISO 639-1 language code")
+    ),
+    responses(
+        (status = 200, description = "The translation", body = DocumentTranslation),
+        (status = 400, description = "Not an ISO 639-1 language code"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or translation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_translation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, language)): Path<(Uuid, String)>,
+) -> Result<Json<DocumentTranslation>, StatusCode> {
+    let language = language_code(&language)?;
+    load_document(&state, &auth_user, id, SharePermission::View).await?;
+
+    let translation = state
+        .db
+        .get_document_translation(id, &language)
+        .await
+        .map_err(|e| {
+            error!("Failed to get translation of document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(translation))
+}
+
+/// Remove the document's translation into a language
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}/translations/{language}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        ("language" = String, Path, description = "ISO 639-1 language code")
+    ),
+    responses(
+        (status = 204, description = "Translation removed"),
+        (status = 400, description = "Not an ISO 639-1 language code"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or translation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_document_translation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, language)): Path<(Uuid, String)>,
+) -> Result<StatusCode, StatusCode> {
+    let language = language_code(&language)?;
+    load_document(&state, &auth_user, id, SharePermission::Edit).await?;
+
+    let deleted = state.db.delete_document_translation(id, &language).await.map_err(|e| {
+        error!("Failed to delete translation of document {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
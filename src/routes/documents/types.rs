@@ -4,8 +4,13 @@ use utoipa::{ToSchema, IntoParams};
 #[derive(Deserialize, ToSchema, IntoParams)]
 pub struct PaginationQuery {
     pub limit: Option<i64>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Deprecated in favor of `cursor`; cannot be combined with it
     pub offset: Option<i64>,
     pub ocr_status: Option<String>,
+    /// Count the matching documents, default true
+    pub include_total: Option<bool>,
 }
 
 #[derive(Deserialize, ToSchema, IntoParams)]
@@ -14,6 +19,9 @@ pub struct FailedDocumentsQuery {
     pub offset: Option<i64>,
     pub stage: Option<String>,  // 'ocr', 'ingestion', 'validation', etc.
     pub reason: Option<String>, // 'duplicate_content', 'low_ocr_confidence', etc.
+    pub category: Option<String>, // 'damaged_file', 'storage', etc., see FailureCategory
+    /// Also list failures that were requeued, defaults to false
+    pub include_requeued: Option<bool>,
 }
 
 #[derive(Deserialize, Serialize, ToSchema)]
@@ -21,6 +29,12 @@ pub struct BulkDeleteRequest {
     pub document_ids: Vec<uuid::Uuid>,
 }
 
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RenameDocumentRequest {
+    /// New name, without a folder
+    pub filename: String,
+}
+
 #[derive(Deserialize, Serialize, ToSchema)]
 pub struct DeleteLowConfidenceRequest {
     pub max_confidence: f32,
@@ -33,6 +47,37 @@ pub struct RetryOcrRequest {
     pub languages: Option<Vec<String>>,
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct RegionOcrRequest {
+    /// 1-based page number
+    pub page: u32,
+    /// Left edge as a fraction (0-1) of the page width
+    pub x: f64,
+    /// Top edge as a fraction (0-1) of the page height
+    pub y: f64,
+    /// Width as a fraction (0-1) of the page width
+    pub width: f64,
+    /// Height as a fraction (0-1) of the page height
+    pub height: f64,
+    /// Render resolution, 150-600 (default 400)
+    pub dpi: Option<u32>,
+    /// Tesseract language code (defaults to the user's OCR language)
+    pub language: Option<String>,
+    /// Append the recognized text to the document's searchable text
+    #[serde(default)]
+    pub append: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RegionOcrResponse {
+    pub text: String,
+    pub page: u32,
+    pub dpi: u32,
+    pub language: String,
+    /// Whether the text was appended to the document's OCR text
+    pub appended: bool,
+}
+
 #[derive(Deserialize, Serialize, ToSchema)]
 pub struct DocumentUploadResponse {
     pub id: uuid::Uuid,
@@ -73,10 +118,14 @@ pub struct DocumentDebugInfo {
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct DocumentPaginationInfo {
-    pub total: i64,
+    /// Documents matching the request; left out with `include_total=false`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
     pub limit: i64,
     pub offset: i64,
     pub has_more: bool,
+    /// Cursor of the next page, None on the last one
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -89,8 +138,10 @@ impl Default for PaginationQuery {
     fn default() -> Self {
         Self {
             limit: Some(25),
+            cursor: None,
             offset: Some(0),
             ocr_status: None,
+            include_total: None,
         }
     }
 }
@@ -102,6 +153,100 @@ impl Default for FailedDocumentsQuery {
             offset: Some(0),
             stage: None,
             reason: None,
+            category: None,
+            include_requeued: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct MergeDocumentsRequest {
+    /// PDF documents to combine, in page order
+    pub document_ids: Vec<uuid::Uuid>,
+    /// Name of the merged document, defaults to `<first document>_merged.pdf`
+    pub filename: Option<String>,
+    /// Delete the source documents once the merged document is stored
+    #[serde(default)]
+    pub delete_originals: bool,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ReorderPagesRequest {
+    /// New page order as 1-based page numbers; every page must be listed once
+    pub page_order: Vec<u32>,
+}
+
+/// Documents to download as one ZIP archive: the listed ones, or those matching a search
+#[derive(Deserialize, ToSchema)]
+pub struct DownloadZipRequest {
+    pub document_ids: Option<Vec<uuid::Uuid>>,
+    /// Search whose matches, in result order, are the documents; needs a query or filters
+    pub filter: Option<crate::models::SavedSearchQuery>,
+    /// Add each document's OCR text next to it as `<filename>.txt`
+    #[serde(default)]
+    pub include_ocr_text: bool,
+}
+
+impl DownloadZipRequest {
+    /// What is wrong with the request, before its documents are resolved
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.document_ids, &self.filter) {
+            (Some(_), Some(_)) => Err("Give either document_ids or a filter, not both".to_string()),
+            (None, None) => Err("Give document_ids or a filter".to_string()),
+            (Some(ids), None) if ids.is_empty() => Err("document_ids is empty".to_string()),
+            (Some(ids), None) if ids.len() > crate::models::MAX_BULK_DOCUMENTS => {
+                Err(format!("At most {} documents are allowed", crate::models::MAX_BULK_DOCUMENTS))
+            }
+            (None, Some(filter)) if filter.is_unrestricted() => {
+                Err("A filter needs a query, tags or MIME types".to_string())
+            }
+            _ => Ok(()),
         }
     }
-}
\ No newline at end of file
+}
+
+/// What a phone app sends along with the photos of a capture upload, as the `metadata`
+/// part of `POST /api/documents/capture`
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct CaptureMetadata {
+    /// One entry per `page` part, in the same order; photos without an entry are used
+    /// as they are
+    #[serde(default)]
+    pub pages: Vec<CapturePageMetadata>,
+    pub device: Option<CaptureDevice>,
+    /// When the first photo was taken
+    pub captured_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Name of the assembled PDF, defaults to `capture-<date>-<time>.pdf`
+    pub filename: Option<String>,
+    /// Stretch the contrast of the pages, default true; turn it off for photos of
+    /// pictures rather than text
+    pub enhance: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct CapturePageMetadata {
+    /// Corners of the document on the photo, clockwise from the top left, as fractions
+    /// of the photo's width and height in its stored orientation. The page is cut out
+    /// and straightened to a rectangle.
+    pub corners: Option<Vec<CapturePoint>>,
+    /// Degrees to turn the page clockwise once it is straightened: 0, 90, 180 or 270
+    #[serde(default)]
+    pub rotation: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct CapturePoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// The phone that made a capture, kept with the document
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct CaptureDevice {
+    /// e.g. "Pixel 8"
+    pub model: Option<String>,
+    /// e.g. "Android 15"
+    pub os: Option<String>,
+    /// Name and version of the capturing app
+    pub app: Option<String>,
+}
@@ -0,0 +1,214 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        DiffLineKind, Document, DocumentResponse, DocumentVersionDiffQuery, DocumentVersionDiffResponse,
+        DocumentVersionResponse, DocumentVersionsResponse,
+    },
+    services::document_version_service::{diff_lines, DocumentVersionService},
+    services::redaction_service,
+    services::event_service::EventService,
+    AppState,
+};
+
+async fn load_document(state: &AppState, auth_user: &AuthUser, document_id: Uuid) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// List the versions of a document, newest first
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/versions",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The current version followed by earlier versions", body = DocumentVersionsResponse),
+        (status = 404, description = "Document not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_versions(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<DocumentVersionsResponse>, StatusCode> {
+    let document = load_document(&state, &auth_user, document_id).await?;
+    let mut earlier = state.db.get_document_versions(document_id).await.map_err(|e| {
+        error!("Failed to load versions of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let may_see_unredacted = redaction_service::may_see_unredacted(&auth_user.user, &document);
+
+    let current_version = earlier.first().map_or(1, |version| version.version_number + 1);
+    let mut versions = vec![DocumentVersionResponse {
+        version_number: current_version,
+        filename: document.filename,
+        file_size: document.file_size,
+        file_hash: document.file_hash,
+        mime_type: document.mime_type,
+        ocr_confidence: document.ocr_confidence,
+        ocr_word_count: document.ocr_word_count,
+        original_modified_at: document.original_modified_at,
+        created_at: earlier.first().map_or(document.created_at, |version| version.superseded_at),
+        superseded_at: None,
+        current: true,
+        restricted: false,
+    }];
+    if !may_see_unredacted {
+        earlier.retain(|version| !version.restricted);
+    }
+    versions.extend(earlier.into_iter().map(DocumentVersionResponse::from));
+
+    Ok(Json(DocumentVersionsResponse {
+        document_id,
+        current_version,
+        versions,
+    }))
+}
+
+/// Bring back an earlier version of a document; the current contents are kept as
+/// a new version
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/versions/{version}/restore",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("version" = i32, Path, description = "Version number to restore")
+    ),
+    responses(
+        (status = 200, description = "Document with the restored contents, queued for OCR", body = DocumentResponse),
+        (status = 404, description = "Document or version not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 423, description = "The document is on legal hold"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn restore_document_version(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((document_id, version_number)): Path<(Uuid, i32)>,
+) -> Result<Json<DocumentResponse>, StatusCode> {
+    let document = load_document(&state, &auth_user, document_id).await?;
+    crate::routes::legal_holds::ensure_not_held(&state, document_id).await?;
+    if !redaction_service::may_see_unredacted(&auth_user.user, &document) {
+        let version = state.db.get_document_version(document_id, version_number).await.map_err(|e| {
+            error!("Failed to load version {} of document {}: {}", version_number, document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        // Restoring would undo a redaction for someone who may not see what it removed
+        if version.is_some_and(|version| version.restricted) {
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
+
+    let service = DocumentVersionService::new(state.db.clone(), state.file_service.as_ref().clone());
+    let restored = service
+        .restore_version(&document, version_number)
+        .await
+        .map_err(|e| {
+            error!("Failed to restore version {} of document {}: {}", version_number, document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let priority = 5; // Same priority as direct uploads
+    if let Err(e) = state.queue_service.enqueue_document(restored.id, priority, restored.file_size).await {
+        error!("Failed to enqueue restored document {} for OCR: {}", restored.id, e);
+    }
+
+    EventService::new(state.db.clone())
+        .publish_document_updated(&restored, &["file"])
+        .await;
+
+    info!("User {} restored document {} to version {}", auth_user.user.id, document_id, version_number);
+    Ok(Json(restored.into()))
+}
+
+/// Compare the OCR texts of two versions of a document line by line
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/versions/diff",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        DocumentVersionDiffQuery
+    ),
+    responses(
+        (status = 200, description = "Lines removed from and added to the older version's text", body = DocumentVersionDiffResponse),
+        (status = 404, description = "Document or version not found"),
+        (status = 409, description = "A version has no OCR text yet"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_version_diff(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Query(query): Query<DocumentVersionDiffQuery>,
+) -> Result<Json<DocumentVersionDiffResponse>, StatusCode> {
+    let document = load_document(&state, &auth_user, document_id).await?;
+    let versions = state.db.get_document_versions(document_id).await.map_err(|e| {
+        error!("Failed to load versions of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let current_version = versions.first().map_or(1, |version| version.version_number + 1);
+    let may_see_unredacted = redaction_service::may_see_unredacted(&auth_user.user, &document);
+
+    let ocr_text = |version_number: i32| -> Result<&str, StatusCode> {
+        let text = if version_number == current_version {
+            document.ocr_text.as_deref()
+        } else {
+            versions
+                .iter()
+                .find(|version| version.version_number == version_number)
+                .filter(|version| may_see_unredacted || !version.restricted)
+                .ok_or(StatusCode::NOT_FOUND)?
+                .ocr_text
+                .as_deref()
+        };
+        text.ok_or(StatusCode::CONFLICT)
+    };
+
+    let to_version = query.to.unwrap_or(current_version);
+    let lines = diff_lines(ocr_text(query.from)?, ocr_text(to_version)?);
+    let count = |kind: DiffLineKind| lines.iter().filter(|line| line.kind == kind).count() as i64;
+
+    Ok(Json(DocumentVersionDiffResponse {
+        document_id,
+        from_version: query.from,
+        to_version,
+        lines_added: count(DiffLineKind::Added),
+        lines_removed: count(DiffLineKind::Removed),
+        lines,
+    }))
+}
@@ -678,7 +678,7 @@ async fn get_filtered_documents(
     Ok(documents)
 }
 
-async fn reset_document_ocr_status(state: &Arc<AppState>, document_id: Uuid) -> Result<(), anyhow::Error> {
+pub(crate) async fn reset_document_ocr_status(state: &Arc<AppState>, document_id: Uuid) -> Result<(), anyhow::Error> {
     sqlx::query(
         r#"
         UPDATE documents
@@ -0,0 +1,380 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        DocumentEmbargo, EmbargoDocumentsRequest, EmbargoDocumentsResponse, ListEmbargoesQuery,
+        ReleaseEmbargoesRequest, ReleaseEmbargoesResponse, SourceEmbargo, UpdateSourceEmbargo, UserRole,
+        MAX_BULK_DOCUMENTS,
+    },
+    routes::queue::require_admin,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_embargoes).post(embargo_documents))
+        .route("/release", post(release_embargoes))
+        .route("/documents/{document_id}", get(get_document_embargo))
+        .route(
+            "/sources/{source_id}",
+            get(get_source_embargo).put(set_source_embargo).delete(delete_source_embargo),
+        )
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+async fn ensure_source_exists(state: &AppState, source_id: Uuid) -> Result<(), StatusCode> {
+    state
+        .db
+        .get_source_by_id(source_id)
+        .await
+        .map_err(|e| internal_error("Failed to get source", e))?
+        .map(|_| ())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Embargoes, ending soonest first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/embargoes",
+    tag = "embargoes",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(ListEmbargoesQuery),
+    responses(
+        (status = 200, description = "Embargoes; only active ones unless include_inactive", body = Vec<DocumentEmbargo>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_embargoes(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<ListEmbargoesQuery>,
+) -> Result<Json<Vec<DocumentEmbargo>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let embargoes = state
+        .db
+        .list_embargoes(query.include_inactive, limit, offset)
+        .await
+        .map_err(|e| internal_error("Failed to list embargoes", e))?;
+    Ok(Json(embargoes))
+}
+
+/// Embargo documents until a date or until they are released (admin only)
+///
+/// Embargoed documents are only visible to admins and their owners: shares, workspaces,
+/// share links and guest portals do not reach them. An earlier embargo of a document
+/// is replaced.
+#[utoipa::path(
+    post,
+    path = "/api/embargoes",
+    tag = "embargoes",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = EmbargoDocumentsRequest,
+    responses(
+        (status = 200, description = "Documents embargoed and not found", body = EmbargoDocumentsResponse),
+        (status = 400, description = "No documents, too many, or a date in the past"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn embargo_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<EmbargoDocumentsRequest>,
+) -> Result<Json<EmbargoDocumentsResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate(Utc::now()) {
+        debug!("Rejected embargo: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let reason = request.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+    let embargoed = state
+        .db
+        .embargo_documents(&request.document_ids, request.until, reason, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to embargo documents", e))?;
+
+    let found: HashSet<Uuid> = embargoed.iter().copied().collect();
+    let not_found = request.document_ids.iter().filter(|id| !found.contains(id)).copied().collect();
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::EMBARGO_CREATE, "document", None).details(serde_json::json!({
+            "document_ids": embargoed,
+            "until": request.until,
+            "reason": reason,
+        })),
+    )
+    .await;
+
+    info!("Admin {} embargoed {} documents until {:?}", auth_user.user.id, embargoed.len(), request.until);
+    Ok(Json(EmbargoDocumentsResponse { embargoed, not_found }))
+}
+
+/// Release embargoed documents so they are visible again (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/embargoes/release",
+    tag = "embargoes",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = ReleaseEmbargoesRequest,
+    responses(
+        (status = 200, description = "Documents released and not embargoed", body = ReleaseEmbargoesResponse),
+        (status = 400, description = "No documents, or too many"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn release_embargoes(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<ReleaseEmbargoesRequest>,
+) -> Result<Json<ReleaseEmbargoesResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    if request.document_ids.is_empty() || request.document_ids.len() > MAX_BULK_DOCUMENTS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let released = state
+        .db
+        .release_embargoes(&request.document_ids, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to release embargoes", e))?;
+
+    let found: HashSet<Uuid> = released.iter().copied().collect();
+    let not_embargoed = request.document_ids.iter().filter(|id| !found.contains(id)).copied().collect();
+
+    if !released.is_empty() {
+        audit_service::record(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::EMBARGO_RELEASE, "document", None)
+                .details(serde_json::json!({ "document_ids": released })),
+        )
+        .await;
+    }
+
+    info!("Admin {} released the embargo of {} documents", auth_user.user.id, released.len());
+    Ok(Json(ReleaseEmbargoesResponse { released, not_embargoed }))
+}
+
+/// The embargo of a document, active or not; its owner or an admin
+#[utoipa::path(
+    get,
+    path = "/api/embargoes/documents/{document_id}",
+    tag = "embargoes",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("document_id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Embargo of the document", body = DocumentEmbargo),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found, or never embargoed"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_embargo(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<DocumentEmbargo>, StatusCode> {
+    let document = state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if auth_user.user.role != UserRole::Admin && document.user_id != auth_user.user.id {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let embargo = state
+        .db
+        .get_document_embargo(document_id)
+        .await
+        .map_err(|e| internal_error("Failed to get embargo", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(embargo))
+}
+
+/// The embargo a source puts on the documents it ingests (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/embargoes/sources/{source_id}",
+    tag = "embargoes",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("source_id" = Uuid, Path, description = "Source ID")
+    ),
+    responses(
+        (status = 200, description = "Embargo of the source", body = SourceEmbargo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Source not found, or without an embargo"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_source_embargo(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(source_id): Path<Uuid>,
+) -> Result<Json<SourceEmbargo>, StatusCode> {
+    require_admin(&auth_user)?;
+    let embargo = state
+        .db
+        .get_source_embargo(source_id)
+        .await
+        .map_err(|e| internal_error("Failed to get source embargo", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(embargo))
+}
+
+/// Embargo every document a source ingests from now on (admin only)
+///
+/// Each document stays hidden for `embargo_days` after it is ingested, or until it is
+/// released when no number of days is given. Documents already ingested are not affected.
+#[utoipa::path(
+    put,
+    path = "/api/embargoes/sources/{source_id}",
+    tag = "embargoes",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("source_id" = Uuid, Path, description = "Source ID")
+    ),
+    request_body = UpdateSourceEmbargo,
+    responses(
+        (status = 200, description = "Embargo of the source", body = SourceEmbargo),
+        (status = 400, description = "Invalid number of days"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Source not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn set_source_embargo(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(source_id): Path<Uuid>,
+    Json(request): Json<UpdateSourceEmbargo>,
+) -> Result<Json<SourceEmbargo>, StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected source embargo: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    ensure_source_exists(&state, source_id).await?;
+
+    let before = state
+        .db
+        .get_source_embargo(source_id)
+        .await
+        .map_err(|e| internal_error("Failed to get source embargo", e))?;
+    let reason = request.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+    let embargo = state
+        .db
+        .set_source_embargo(source_id, request.embargo_days, reason, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to set source embargo", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SOURCE_EMBARGO_UPDATE, "source", Some(source_id))
+            .before(serde_json::json!(before))
+            .details(serde_json::json!({ "embargo_days": embargo.embargo_days, "reason": embargo.reason })),
+    )
+    .await;
+
+    Ok(Json(embargo))
+}
+
+/// Stop embargoing what a source ingests; documents already embargoed stay so (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/embargoes/sources/{source_id}",
+    tag = "embargoes",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("source_id" = Uuid, Path, description = "Source ID")
+    ),
+    responses(
+        (status = 204, description = "Embargo removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Source without an embargo"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_source_embargo(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(source_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+    let deleted = state
+        .db
+        .delete_source_embargo(source_id)
+        .await
+        .map_err(|e| internal_error("Failed to delete source embargo", e))?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SOURCE_EMBARGO_UPDATE, "source", Some(source_id))
+            .details(serde_json::json!({ "embargo_days": null, "removed": true })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,193 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::{error, info};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{EncryptionKey, EncryptionKeyEvent, KeyOwner, RotateEncryptionKeyRequest, WorkspaceRole},
+    routes::queue::require_admin,
+    services::encryption::{self, EncryptionService},
+    services::workspace_service,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/keys", get(list_encryption_keys))
+        .route("/keys/rotate", post(rotate_encryption_key))
+        .route("/events", get(list_encryption_key_events))
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct EncryptionQuery {
+    /// Another user's keys; admins only. Ignored for requests in a workspace, which are
+    /// about the workspace's keys.
+    pub user_id: Option<Uuid>,
+    /// Maximum number of audit events, 100 by default
+    pub limit: Option<i64>,
+}
+
+/// Whose keys a request is about: the workspace it is made in, which takes the
+/// workspace admin role, otherwise the caller, or for admins any user
+async fn target_owner(state: &AppState, auth_user: &AuthUser, query: &EncryptionQuery) -> Result<KeyOwner, StatusCode> {
+    if let Some(workspace_id) = auth_user.workspace_id {
+        let allowed = workspace_service::has_role(&state.db, &auth_user.user, workspace_id, WorkspaceRole::Admin)
+            .await
+            .map_err(|e| {
+                error!("Failed to look up workspace role: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if !allowed {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        return Ok(KeyOwner::Workspace(workspace_id));
+    }
+
+    match query.user_id {
+        Some(user_id) if user_id != auth_user.user.id => {
+            require_admin(auth_user)?;
+            Ok(KeyOwner::User(user_id))
+        }
+        _ => Ok(KeyOwner::User(auth_user.user.id)),
+    }
+}
+
+fn encryption_service(state: &AppState) -> Result<&Arc<EncryptionService>, StatusCode> {
+    state.file_service.encryption().ok_or(StatusCode::NOT_IMPLEMENTED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/encryption/keys",
+    tag = "encryption",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(EncryptionQuery),
+    responses(
+        (status = 200, description = "Key versions, newest first", body = Vec<EncryptionKey>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only admins can list other users' keys, and only workspace admins the workspace's"),
+        (status = 501, description = "Encryption is not enabled on this server"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_encryption_keys(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<EncryptionQuery>,
+) -> Result<Json<Vec<EncryptionKey>>, StatusCode> {
+    let owner = target_owner(&state, &auth_user, &query).await?;
+    encryption_service(&state)?;
+
+    let keys = state.db.get_encryption_keys(owner).await.map_err(|e| {
+        error!("Failed to list encryption keys of {}: {}", owner, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(keys))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/encryption/keys/rotate",
+    tag = "encryption",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(EncryptionQuery),
+    request_body = RotateEncryptionKeyRequest,
+    responses(
+        (status = 200, description = "The new active key version", body = EncryptionKey),
+        (status = 400, description = "Unsupported KMS key reference"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only admins can rotate other users' keys, and only workspace admins the workspace's"),
+        (status = 501, description = "Encryption is not enabled on this server"),
+        (status = 502, description = "The KMS rejected the wrap or unwrap request")
+    )
+)]
+pub async fn rotate_encryption_key(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<EncryptionQuery>,
+    Json(request): Json<RotateEncryptionKeyRequest>,
+) -> Result<Json<EncryptionKey>, StatusCode> {
+    let owner = target_owner(&state, &auth_user, &query).await?;
+    let encryption = encryption_service(&state)?.clone();
+
+    if encryption::kms::KmsKeyRef::parse(request.kms_key_ref.as_deref()).is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let key = encryption
+        .rotate(owner, request.kms_key_ref.as_deref(), Some(auth_user.user.id))
+        .await
+        .map_err(|e| {
+            error!("Failed to rotate encryption key of {}: {}", owner, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    if request.reencrypt_files {
+        let db = state.db.clone();
+        let file_service = state.file_service.clone();
+        let actor_user_id = auth_user.user.id;
+        spawn_guarded("Re-encrypt files", async move {
+            match encryption::reencrypt_owner_data(&db, &file_service, owner).await {
+                Ok((rewritten, failed)) => {
+                    info!("Re-encrypted {} files and sources of {} ({} failed)", rewritten, owner, failed);
+                    let detail = format!("{} files and sources re-encrypted, {} failed", rewritten, failed);
+                    let active_key_id = encryption.active_key(owner).await.ok().map(|key| key.id);
+                    encryption
+                        .record_event(active_key_id, owner, "reencrypted", Some(actor_user_id), Some(&detail))
+                        .await;
+                }
+                Err(e) => error!("Re-encrypting files of {} failed: {}", owner, e),
+            }
+        });
+    }
+
+    Ok(Json(key))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/encryption/events",
+    tag = "encryption",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(EncryptionQuery),
+    responses(
+        (status = 200, description = "Key lifecycle and usage audit, newest first", body = Vec<EncryptionKeyEvent>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only admins can read other users' audit, and only workspace admins the workspace's"),
+        (status = 501, description = "Encryption is not enabled on this server"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_encryption_key_events(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<EncryptionQuery>,
+) -> Result<Json<Vec<EncryptionKeyEvent>>, StatusCode> {
+    let owner = target_owner(&state, &auth_user, &query).await?;
+    encryption_service(&state)?;
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let events = state.db.get_encryption_key_events(owner, limit).await.map_err(|e| {
+        error!("Failed to list encryption key events of {}: {}", owner, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(events))
+}
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{EntitiesQuery, EntityProfile, GraphEntitySummary, UserRole},
+    services::entity_service::EntityService,
+    AppState,
+};
+
+const DEFAULT_ENTITY_LIMIT: i64 = 50;
+const MAX_ENTITY_LIMIT: i64 = 200;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_entities))
+        .route("/{id}", get(get_entity))
+}
+
+/// The user's knowledge-graph entities, by name
+#[utoipa::path(
+    get,
+    path = "/api/entities",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(EntitiesQuery),
+    responses(
+        (status = 200, description = "Entities with the number of documents mentioning them", body = Vec<GraphEntitySummary>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_entities(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<EntitiesQuery>,
+) -> Result<Json<Vec<GraphEntitySummary>>, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_ENTITY_LIMIT).clamp(1, MAX_ENTITY_LIMIT);
+    let name = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let entities = state
+        .db
+        .list_graph_entities(auth_user.user.id, name, query.label.as_deref(), limit)
+        .await
+        .map_err(|e| {
+            error!("Failed to list entities: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(entities))
+}
+
+/// An entity's page: the documents mentioning it, its relationships, its attributes
+/// merged across documents and a timeline of mentions
+#[utoipa::path(
+    get,
+    path = "/api/entities/{id}",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Entity ID")
+    ),
+    responses(
+        (status = 200, description = "Entity profile", body = EntityProfile),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Entity not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_entity(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<EntityProfile>, StatusCode> {
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to build profile of entity {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let entity = state
+        .db
+        .get_graph_entity(id)
+        .await
+        .map_err(db_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if entity.user_id != auth_user.user.id && auth_user.user.role != UserRole::Admin {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let profile = EntityService::new(state.db.clone()).profile(entity).await.map_err(db_error)?;
+    Ok(Json(profile))
+}
@@ -0,0 +1,386 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Path, Query, State, WebSocketUpgrade,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        EventEnvelope, EventSchemaResponse, EventStreamQuery, EventType, ReplayEventsRequest, ReplayEventsResponse,
+        StoredEvent,
+    },
+    routes::queue::require_admin,
+    routes::sources::sync::extract_websocket_token_and_protocol,
+    services::event_service::{self, EventService},
+    AppState,
+};
+
+/// Missed events sent to a reconnecting client before it is asked to reload instead
+const MAX_CATCH_UP_EVENTS: i64 = 1000;
+/// How often an open stream is pinged and re-checks that its user is still logged in
+const STREAM_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/schemas", get(list_event_schemas))
+        .route("/schemas/{event_type}", get(get_event_schema))
+        .route("/replay", post(replay_events))
+        .route("/ws", get(event_stream_websocket))
+}
+
+fn schema_response(event_type: EventType) -> EventSchemaResponse {
+    EventSchemaResponse {
+        event_type,
+        schema_version: event_type.schema_version(),
+        schema: event_service::envelope_schema(event_type),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/events/schemas",
+    tag = "events",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Versioned JSON schemas for every event type", body = Vec<EventSchemaResponse>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn list_event_schemas(
+    _auth_user: AuthUser,
+) -> Json<Vec<EventSchemaResponse>> {
+    Json(EventType::ALL.iter().copied().map(schema_response).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/events/schemas/{event_type}",
+    tag = "events",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("event_type" = String, Path, description = "Event type, e.g. document.created")
+    ),
+    responses(
+        (status = 200, description = "JSON schema for the event type", body = EventSchemaResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Unknown event type")
+    )
+)]
+pub async fn get_event_schema(
+    _auth_user: AuthUser,
+    Path(event_type): Path<String>,
+) -> Result<Json<EventSchemaResponse>, StatusCode> {
+    let event_type = EventType::try_from(event_type).map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(Json(schema_response(event_type)))
+}
+
+/// Re-deliver stored events to a URL, one batch per request
+#[utoipa::path(
+    post,
+    path = "/api/events/replay",
+    tag = "events",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = ReplayEventsRequest,
+    responses(
+        (status = 200, description = "Batch of the replay sent", body = ReplayEventsResponse),
+        (status = 400, description = "Invalid time range, target URL or event to continue after"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn replay_events(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<ReplayEventsRequest>,
+) -> Result<Json<ReplayEventsResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    if request.from >= request.to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let target = url::Url::parse(&request.target_url).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if target.scheme() != "http" && target.scheme() != "https" {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let after = match request.after_event_id {
+        Some(id) => {
+            let event = state.db.get_event(id).await.map_err(|e| {
+                error!("Failed to look up event {}: {}", id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            Some((event.ok_or(StatusCode::BAD_REQUEST)?.occurred_at, id))
+        }
+        None => None,
+    };
+
+    let service = EventService::new(state.db.clone());
+    let result = service
+        .replay(request.from, request.to, after, request.event_types.as_deref(), target.as_str())
+        .await
+        .map_err(|e| {
+            error!("Failed to replay events: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(result))
+}
+
+/// WebSocket stream of the user's events as they happen
+///
+/// Every message is a JSON object with a `type` and `data`:
+/// - `connection_confirmed`: the stream is open
+/// - `event`: an event envelope, as delivered to webhooks, including `queue.progress`
+///   events that are only streamed
+/// - `resync_required`: events were missed and cannot be caught up on; reload the state
+/// - `error`: the stream is closing, e.g. because the session ended
+///
+/// A client that reconnects with the id of the last event it received first gets the
+/// stored events it missed, up to 1000. Authentication works like the sync progress
+/// socket, with the JWT in the `Sec-WebSocket-Protocol` header.
+#[utoipa::path(
+    get,
+    path = "/api/events/ws",
+    tag = "events",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(EventStreamQuery),
+    responses(
+        (status = 101, description = "WebSocket connection established - will stream events"),
+        (status = 400, description = "Unknown event type"),
+        (status = 401, description = "Unauthorized - invalid or missing authentication token"),
+        (status = 500, description = "Internal server error during WebSocket upgrade")
+    )
+)]
+pub async fn event_stream_websocket(
+    ws: WebSocketUpgrade,
+    Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, StatusCode> {
+    let (token, auth_protocol) = extract_websocket_token_and_protocol(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let (user_id, session_id) = crate::auth::authenticate_jwt(&state, &token)
+        .await
+        .map_err(|response| response.status())?;
+    state
+        .db
+        .get_user_by_id(user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let event_types = query.parse_event_types().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // Some(None): the client resumes from an event it may not see or that no longer exists
+    let resume_from = match query.last_event_id {
+        Some(event_id) => Some(
+            state
+                .db
+                .get_event(event_id)
+                .await
+                .map_err(|e| {
+                    error!("Failed to get event {}: {}", event_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .filter(|event| event.user_id == Some(user_id)),
+        ),
+        None => None,
+    };
+
+    let stream = EventStream { state, user_id, session_id, event_types };
+    Ok(ws
+        .protocols([auth_protocol])
+        .on_upgrade(move |socket| stream.run(socket, resume_from)))
+}
+
+fn stream_message(kind: &str, data: serde_json::Value) -> Message {
+    Message::Text(serde_json::json!({ "type": kind, "data": data }).to_string().into())
+}
+
+/// One client's stream of its user's events
+struct EventStream {
+    state: Arc<AppState>,
+    user_id: Uuid,
+    session_id: Option<Uuid>,
+    event_types: Option<Vec<EventType>>,
+}
+
+impl EventStream {
+    fn wants(&self, envelope: &EventEnvelope) -> bool {
+        envelope.user_id == Some(self.user_id)
+            && self.event_types.as_ref().is_none_or(|types| types.contains(&envelope.event_type))
+    }
+
+    /// Whether the user still exists and is still logged in
+    async fn access_allowed(&self) -> bool {
+        if let Some(session_id) = self.session_id {
+            if !matches!(self.state.db.get_active_session(session_id).await, Ok(Some(_))) {
+                return false;
+            }
+        }
+        matches!(self.state.db.get_user_by_id(self.user_id).await, Ok(Some(_)))
+    }
+
+    async fn run(self, mut socket: WebSocket, resume_from: Option<Option<StoredEvent>>) {
+        // Subscribe before catching up so nothing published in between is lost
+        let mut events = event_service::subscribe();
+        info!("Event stream opened for user {}", self.user_id);
+
+        let confirmation = stream_message(
+            "connection_confirmed",
+            serde_json::json!({
+                "user_id": self.user_id,
+                "resumed": matches!(resume_from, Some(Some(_))),
+                "timestamp": Utc::now().timestamp(),
+            }),
+        );
+        if socket.send(confirmation).await.is_err() {
+            return;
+        }
+
+        // Position of the last stored event sent, to catch up from after falling behind
+        let mut position: Option<(DateTime<Utc>, Uuid)> = None;
+        // Events sent while catching up that may also still arrive from the bus
+        let mut caught_up: HashSet<Uuid> = HashSet::new();
+
+        match resume_from {
+            Some(Some(event)) => {
+                match self.catch_up(&mut socket, (event.occurred_at, event.id), &mut caught_up).await {
+                    Ok(caught_up_to) => position = caught_up_to,
+                    Err(_) => return,
+                }
+            }
+            Some(None) => {
+                let resync = stream_message("resync_required", serde_json::json!({ "reason": "unknown_event_id" }));
+                if socket.send(resync).await.is_err() {
+                    return;
+                }
+            }
+            None => {}
+        }
+
+        let mut checks = tokio::time::interval(STREAM_CHECK_INTERVAL);
+        checks.tick().await;
+
+        loop {
+            tokio::select! {
+                received = events.recv() => match received {
+                    Ok(envelope) => {
+                        if !self.wants(&envelope) || caught_up.remove(&envelope.event_id) {
+                            continue;
+                        }
+                        if envelope.event_type.is_stored() {
+                            position = Some((envelope.occurred_at, envelope.event_id));
+                        }
+                        if socket.send(stream_message("event", serde_json::json!(envelope))).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Event stream of user {} fell {} events behind", self.user_id, missed);
+                        let caught_up_to = match position {
+                            Some(from) => self.catch_up(&mut socket, from, &mut caught_up).await,
+                            None => socket
+                                .send(stream_message("resync_required", serde_json::json!({ "reason": "lagged" })))
+                                .await
+                                .map(|_| None),
+                        };
+                        match caught_up_to {
+                            Ok(caught_up_to) => position = caught_up_to,
+                            Err(_) => break,
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                message = socket.recv() => match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+                _ = checks.tick() => {
+                    if !self.access_allowed().await {
+                        info!("Closing event stream of user {}: access was revoked", self.user_id);
+                        let revoked = stream_message(
+                            "error",
+                            serde_json::json!({
+                                "message": "Access was revoked",
+                                "error_type": "access_revoked",
+                            }),
+                        );
+                        let _ = socket.send(revoked).await;
+                        break;
+                    }
+                    if socket.send(Message::Ping(vec![].into())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        info!("Event stream closed for user {}", self.user_id);
+    }
+
+    /// Send the stored events after `from` that the client wants. Returns the position
+    /// to continue from, or None after asking the client to resync because too many
+    /// events were missed or they could not be loaded.
+    async fn catch_up(
+        &self,
+        socket: &mut WebSocket,
+        from: (DateTime<Utc>, Uuid),
+        caught_up: &mut HashSet<Uuid>,
+    ) -> Result<Option<(DateTime<Utc>, Uuid)>, axum::Error> {
+        let missed = match self
+            .state
+            .db
+            .get_user_events_after(self.user_id, from, self.event_types.as_deref(), MAX_CATCH_UP_EVENTS + 1)
+            .await
+        {
+            Ok(missed) if missed.len() as i64 <= MAX_CATCH_UP_EVENTS => missed,
+            Ok(_) => {
+                socket
+                    .send(stream_message("resync_required", serde_json::json!({ "reason": "too_many_missed_events" })))
+                    .await?;
+                return Ok(None);
+            }
+            Err(e) => {
+                error!("Failed to load missed events of user {}: {}", self.user_id, e);
+                socket
+                    .send(stream_message("resync_required", serde_json::json!({ "reason": "internal_error" })))
+                    .await?;
+                return Ok(None);
+            }
+        };
+
+        caught_up.clear();
+        let mut position = from;
+        for event in missed {
+            position = (event.occurred_at, event.id);
+            caught_up.insert(event.id);
+            let envelope: EventEnvelope = event.into();
+            socket.send(stream_message("event", serde_json::json!(envelope))).await?;
+        }
+
+        Ok(Some(position))
+    }
+}
@@ -0,0 +1,234 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        Document, DocumentExpiration, SetDocumentExpirationRequest, SharePermission, UpcomingExpirationsQuery,
+        EXPIRATION_KINDS, EXPIRATION_KIND_EXPIRATION,
+    },
+    services::expiration_service::ExpirationService,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/", get(list_upcoming_expirations))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+async fn load_document(
+    state: &AppState,
+    auth_user: &AuthUser,
+    document_id: Uuid,
+    permission: SharePermission,
+) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, permission)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Documents of the current user that expire or renew soon, soonest first
+#[utoipa::path(
+    get,
+    path = "/api/expirations",
+    tag = "expirations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(UpcomingExpirationsQuery),
+    responses(
+        (status = 200, description = "Upcoming expirations", body = Vec<DocumentExpiration>),
+        (status = 400, description = "Unknown kind"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_upcoming_expirations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<UpcomingExpirationsQuery>,
+) -> Result<Json<Vec<DocumentExpiration>>, StatusCode> {
+    if query.kind.as_deref().is_some_and(|kind| !EXPIRATION_KINDS.contains(&kind)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let expirations = state
+        .db
+        .get_upcoming_expirations(auth_user.user.id, &query)
+        .await
+        .map_err(|e| internal_error("Failed to list upcoming expirations", e))?;
+
+    Ok(Json(expirations))
+}
+
+/// When a document expires or renews
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/expiration",
+    tag = "expirations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The document's expiration", body = DocumentExpiration),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found, or it has no expiration"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_expiration(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<DocumentExpiration>, StatusCode> {
+    load_document(&state, &auth_user, document_id, SharePermission::View).await?;
+    let expiration = state
+        .db
+        .get_document_expiration(document_id)
+        .await
+        .map_err(|e| internal_error("Failed to get document expiration", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(expiration))
+}
+
+/// Set when a document expires or renews
+///
+/// Dates set by hand are never replaced by extraction. A new date or notice period
+/// notifies the owner again when it is due.
+#[utoipa::path(
+    put,
+    path = "/api/documents/{id}/expiration",
+    tag = "expirations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    request_body = SetDocumentExpirationRequest,
+    responses(
+        (status = 200, description = "Expiration set", body = DocumentExpiration),
+        (status = 400, description = "Unknown kind, notice period out of range or note too long"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn set_document_expiration(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Json(request): Json<SetDocumentExpirationRequest>,
+) -> Result<Json<DocumentExpiration>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected expiration of document {}: {}", document_id, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    load_document(&state, &auth_user, document_id, SharePermission::Edit).await?;
+
+    let note = request.note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+    let expiration = state
+        .db
+        .set_document_expiration(
+            document_id,
+            request.expires_on,
+            request.kind.as_deref().unwrap_or(EXPIRATION_KIND_EXPIRATION),
+            request.notice_days.unwrap_or_else(ExpirationService::default_notice_days),
+            note,
+            auth_user.user.id,
+        )
+        .await
+        .map_err(|e| internal_error("Failed to set document expiration", e))?;
+
+    Ok(Json(expiration))
+}
+
+/// Remove a document's expiration
+#[utoipa::path(
+    delete,
+    path = "/api/documents/{id}/expiration",
+    tag = "expirations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 204, description = "Expiration removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found, or it has no expiration"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_document_expiration(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    load_document(&state, &auth_user, document_id, SharePermission::Edit).await?;
+    let deleted = state
+        .db
+        .delete_document_expiration(document_id)
+        .await
+        .map_err(|e| internal_error("Failed to delete document expiration", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Look for the expiration or renewal date in the document's text now
+///
+/// A date set by hand is kept; dates already past are ignored.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/expiration/extract",
+    tag = "expirations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The document's expiration, found now or set before", body = DocumentExpiration),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found, or no upcoming date in its text"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn extract_document_expiration(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<DocumentExpiration>, StatusCode> {
+    let document = load_document(&state, &auth_user, document_id, SharePermission::Edit).await?;
+    let expiration = ExpirationService::new(state.db.clone())
+        .extract(&document)
+        .await
+        .map_err(|e| internal_error("Failed to extract document expiration", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(expiration))
+}
@@ -0,0 +1,267 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{Json, Response},
+    routing::get,
+    Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tokio_util::io::ReaderStream;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{CreateExportRequest, Export, ExportStatus, UserRole},
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::export_service::ExportService,
+    AppState,
+};
+
+/// Exports listed per request
+const EXPORT_LIST_LIMIT: i64 = 50;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_exports).post(create_export))
+        .route("/{id}", get(get_export).delete(delete_export))
+        .route("/{id}/download", get(download_export))
+}
+
+fn export_service(state: &AppState) -> ExportService {
+    ExportService::new(state.db.clone(), state.file_service.as_ref().clone())
+}
+
+/// The export, if the user may see it
+async fn accessible_export(state: &AppState, auth_user: &AuthUser, id: Uuid) -> Result<Export, StatusCode> {
+    let export = state
+        .db
+        .get_export(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get export {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if export.user_id != auth_user.user.id && auth_user.user.role != UserRole::Admin {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(export)
+}
+
+/// Start writing an archive of the library's original files and a manifest of their
+/// metadata, labels, OCR text and knowledge graphs
+#[utoipa::path(
+    post,
+    path = "/api/export",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateExportRequest,
+    responses(
+        (status = 202, description = "Export queued; follow it at /api/export/{id}", body = Export),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only admins can export every user's library"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_export(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<CreateExportRequest>,
+) -> Result<(StatusCode, Json<Export>), StatusCode> {
+    if request.all_users && auth_user.user.role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to start export: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let owner = (!request.all_users).then_some(auth_user.user.id);
+    let document_ids = state.db.get_library_document_ids(owner, None).await.map_err(db_error)?;
+    let export = state
+        .db
+        .create_export(auth_user.user.id, request.all_users, &document_ids)
+        .await
+        .map_err(db_error)?;
+
+    info!(
+        "User {} started export {} of {} documents",
+        auth_user.user.id, export.id, export.total_documents
+    );
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LIBRARY_EXPORT, "export", Some(export.id))
+            .details(json!({ "all_users": export.all_users, "documents": export.total_documents })),
+    )
+    .await;
+
+    let service = export_service(&state);
+    let export_id = export.id;
+    spawn_guarded(format!("export {}", export_id), async move {
+        if let Err(e) = service.run(export_id).await {
+            error!("Export {} stopped: {}", export_id, e);
+        }
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(export)))
+}
+
+/// The user's exports, newest first
+#[utoipa::path(
+    get,
+    path = "/api/export",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Recent exports with their progress", body = Vec<Export>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_exports(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<Export>>, StatusCode> {
+    let exports = state
+        .db
+        .list_exports(auth_user.user.id, EXPORT_LIST_LIMIT)
+        .await
+        .map_err(|e| {
+            error!("Failed to list exports: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(exports))
+}
+
+/// Status and progress of an export
+#[utoipa::path(
+    get,
+    path = "/api/export/{id}",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Export ID")
+    ),
+    responses(
+        (status = 200, description = "Export", body = Export),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Export not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_export(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Export>, StatusCode> {
+    accessible_export(&state, &auth_user, id).await.map(Json)
+}
+
+/// Download the archive of a completed export
+#[utoipa::path(
+    get,
+    path = "/api/export/{id}/download",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Export ID")
+    ),
+    responses(
+        (status = 200, description = "ZIP archive with the files under files/ and manifest.json", content_type = "application/zip"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Export not found"),
+        (status = 409, description = "Export has not completed"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn download_export(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response, StatusCode> {
+    let export = accessible_export(&state, &auth_user, id).await?;
+    if export.status != ExportStatus::Completed {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let archive_path = export_service(&state).archive_path(export.id);
+    let file = tokio::fs::File::open(&archive_path).await.map_err(|e| {
+        error!("Failed to open archive of export {} at {}: {}", export.id, archive_path.display(), e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let filename = format!("readur-export-{}.zip", export.created_at.format("%Y-%m-%d"));
+
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/zip")
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename))
+        .header(header::CACHE_CONTROL, "no-store");
+    if let Some(archive_size) = export.archive_size {
+        response = response.header(header::CONTENT_LENGTH, archive_size.to_string());
+    }
+    response
+        .body(Body::from_stream(ReaderStream::new(file)))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Delete an export and its archive
+#[utoipa::path(
+    delete,
+    path = "/api/export/{id}",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Export ID")
+    ),
+    responses(
+        (status = 204, description = "Export deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Export not found"),
+        (status = 409, description = "Export is still being written"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_export(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let export = accessible_export(&state, &auth_user, id).await?;
+    if matches!(export.status, ExportStatus::Queued | ExportStatus::Running) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    export_service(&state).delete_archive(export.id).await.map_err(|e| {
+        error!("Failed to delete archive of export {}: {}", export.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    state.db.delete_export(export.id).await.map_err(|e| {
+        error!("Failed to delete export {}: {}", export.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
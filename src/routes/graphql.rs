@@ -0,0 +1,107 @@
+use axum::{
+    extract::{RawQuery, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+use crate::{
+    auth::AuthUser,
+    graphql::{self, Viewer, SCHEMA},
+    AppState,
+};
+
+/// The GraphQL endpoints, merged into the API documentation when the feature is built
+#[derive(OpenApi)]
+#[openapi(
+    paths(graphql_post, graphql_get, graphql_schema),
+    tags(
+        (name = "graphql", description = "Read-only GraphQL queries over documents, labels, sources and entities"),
+    )
+)]
+pub struct GraphqlApiDoc;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(graphql_get).post(graphql_post))
+        .route("/schema", get(graphql_schema))
+}
+
+/// Run a GraphQL query sent as JSON
+#[utoipa::path(
+    post,
+    path = "/api/graphql",
+    tag = "graphql",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body(content = serde_json::Value, description = "GraphQL request: `query`, optional `operationName` and `variables`"),
+    responses(
+        (status = 200, description = "GraphQL response with `data` and any `errors`", body = serde_json::Value),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn graphql_post(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(execute(&state, auth_user, request).await)
+}
+
+/// Run a GraphQL query passed in the query string, for read-only API tokens and caches
+#[utoipa::path(
+    get,
+    path = "/api/graphql",
+    tag = "graphql",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("query" = String, Query, description = "The GraphQL query"),
+        ("operationName" = Option<String>, Query, description = "Operation to run when the query has several"),
+        ("variables" = Option<String>, Query, description = "Variables as a JSON object")
+    ),
+    responses(
+        (status = 200, description = "GraphQL response with `data` and any `errors`", body = serde_json::Value),
+        (status = 400, description = "Malformed query string"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn graphql_get(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    RawQuery(query): RawQuery,
+) -> Result<Json<async_graphql::Response>, StatusCode> {
+    let request = async_graphql::http::parse_query_string(query.as_deref().unwrap_or_default())
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    Ok(Json(execute(&state, auth_user, request).await))
+}
+
+/// The schema in GraphQL SDL, for code generators
+#[utoipa::path(
+    get,
+    path = "/api/graphql/schema",
+    tag = "graphql",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The GraphQL schema", body = String, content_type = "text/plain"),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn graphql_schema(_auth_user: AuthUser) -> String {
+    SCHEMA.sdl()
+}
+
+async fn execute(state: &AppState, auth_user: AuthUser, request: async_graphql::Request) -> async_graphql::Response {
+    let viewer = Viewer {
+        user: auth_user.user,
+        workspace_id: auth_user.workspace_id,
+    };
+    graphql::execute(state.db.clone(), viewer, request).await
+}
@@ -0,0 +1,359 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{delete, get},
+    Router,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{AddGroupMemberRequest, CreateUserGroupRequest, UpdateUserGroupRequest, UserGroup, UserGroupMember, UserRole},
+    routes::queue::require_admin,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_groups).post(create_group))
+        .route("/{id}", get(get_group).put(update_group).delete(delete_group))
+        .route("/{id}/members", get(list_group_members).post(add_group_member))
+        .route("/{id}/members/{user_id}", delete(remove_group_member))
+}
+
+fn is_valid_group_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty() && name.len() <= 100
+}
+
+/// A group the user may see: any group for admins, otherwise one they are a member of
+async fn visible_group(state: &AppState, auth_user: &AuthUser, id: Uuid) -> Result<UserGroup, StatusCode> {
+    let group = if auth_user.user.role == UserRole::Admin {
+        state.db.get_user_group(id).await
+    } else {
+        state
+            .db
+            .get_groups_for_user(auth_user.user.id)
+            .await
+            .map(|groups| groups.into_iter().find(|group| group.id == id))
+    }
+    .map_err(|e| {
+        error!("Failed to look up group {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    group.ok_or(StatusCode::NOT_FOUND)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/groups",
+    tag = "groups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Every group for admins, otherwise the groups the current user is a member of", body = Vec<UserGroup>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_groups(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<UserGroup>>, StatusCode> {
+    let groups = if auth_user.user.role == UserRole::Admin {
+        state.db.get_user_groups().await
+    } else {
+        state.db.get_groups_for_user(auth_user.user.id).await
+    }
+    .map_err(|e| {
+        error!("Failed to list groups: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(groups))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/groups",
+    tag = "groups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateUserGroupRequest,
+    responses(
+        (status = 201, description = "Group created", body = UserGroup),
+        (status = 400, description = "Empty or too long name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 409, description = "A group with this name exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_group(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateUserGroupRequest>,
+) -> Result<(StatusCode, Json<UserGroup>), StatusCode> {
+    require_admin(&auth_user)?;
+    if !is_valid_group_name(&request.name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let group = state
+        .db
+        .create_user_group(&request.name, request.description.as_deref(), auth_user.user.id)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("duplicate key") {
+                StatusCode::CONFLICT
+            } else {
+                error!("Failed to create group: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    info!("User {} created group '{}' ({})", auth_user.user.id, group.name, group.id);
+    Ok((StatusCode::CREATED, Json(group)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/groups/{id}",
+    tag = "groups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Group ID")
+    ),
+    responses(
+        (status = 200, description = "Group", body = UserGroup),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found or the current user is not a member"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_group(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<UserGroup>, StatusCode> {
+    visible_group(&state, &auth_user, id).await.map(Json)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/groups/{id}",
+    tag = "groups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Group ID")
+    ),
+    request_body = UpdateUserGroupRequest,
+    responses(
+        (status = 200, description = "Group updated", body = UserGroup),
+        (status = 400, description = "Empty or too long name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Group not found"),
+        (status = 409, description = "A group with this name exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_group(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateUserGroupRequest>,
+) -> Result<Json<UserGroup>, StatusCode> {
+    require_admin(&auth_user)?;
+    let existing = visible_group(&state, &auth_user, id).await?;
+
+    let name = request.name.unwrap_or(existing.name);
+    if !is_valid_group_name(&name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let description = request.description.or(existing.description);
+
+    let updated = state
+        .db
+        .update_user_group(id, &name, description.as_deref())
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("duplicate key") {
+                StatusCode::CONFLICT
+            } else {
+                error!("Failed to update group {}: {}", id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+    if !updated {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    visible_group(&state, &auth_user, id).await.map(Json)
+}
+
+/// Delete a group; everything shared with it stops being shared
+#[utoipa::path(
+    delete,
+    path = "/api/groups/{id}",
+    tag = "groups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Group ID")
+    ),
+    responses(
+        (status = 204, description = "Group deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Group not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_group(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let deleted = state.db.delete_user_group(id).await.map_err(|e| {
+        error!("Failed to delete group {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if deleted {
+        info!("User {} deleted group {}", auth_user.user.id, id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/groups/{id}/members",
+    tag = "groups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Group ID")
+    ),
+    responses(
+        (status = 200, description = "Members of the group", body = Vec<UserGroupMember>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found or the current user is not a member"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_group_members(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<UserGroupMember>>, StatusCode> {
+    visible_group(&state, &auth_user, id).await?;
+
+    let members = state.db.get_user_group_members(id).await.map_err(|e| {
+        error!("Failed to list members of group {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(members))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/groups/{id}/members",
+    tag = "groups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Group ID")
+    ),
+    request_body = AddGroupMemberRequest,
+    responses(
+        (status = 201, description = "Member added"),
+        (status = 200, description = "Already a member"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Group or user not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn add_group_member(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AddGroupMemberRequest>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+    visible_group(&state, &auth_user, id).await?;
+
+    state
+        .db
+        .get_user_by_id(request.user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up user {}: {}", request.user_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let added = state.db.add_user_group_member(id, request.user_id).await.map_err(|e| {
+        error!("Failed to add user {} to group {}: {}", request.user_id, id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(if added { StatusCode::CREATED } else { StatusCode::OK })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/groups/{id}/members/{user_id}",
+    tag = "groups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Group ID"),
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Not a member of the group"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn remove_group_member(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let removed = state.db.remove_user_group_member(id, user_id).await.map_err(|e| {
+        error!("Failed to remove user {} from group {}: {}", user_id, id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if removed {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
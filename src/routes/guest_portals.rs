@@ -0,0 +1,482 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    embedded_metadata,
+    models::{
+        CreateGuestPortalRequest, GuestPortal, GuestPortalDocumentDetail, GuestPortalSearchQuery,
+        GuestPortalSearchResponse, PublicGuestPortal, UpdateGuestPortalRequest,
+    },
+    routes::queue::require_admin,
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        rate_limit_service::{self, RateLimit},
+    },
+    AppState,
+};
+
+/// Managing portals, for admins
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_guest_portals).post(create_guest_portal))
+        .route("/{id}", get(get_guest_portal).patch(update_guest_portal).delete(delete_guest_portal))
+}
+
+/// Browsing portals, without an account
+pub fn public_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/{slug}", get(get_public_portal))
+        .route("/{slug}/documents", get(search_portal_documents))
+        .route("/{slug}/documents/{id}", get(get_portal_document))
+        .route("/{slug}/documents/{id}/download", get(download_portal_document))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// The enabled portal under `slug`, once the visitor's address is within its rate
+/// limit. Unknown and disabled portals are 404 alike.
+async fn open_portal(state: &AppState, client: &ClientInfo, slug: &str) -> Result<GuestPortal, Response> {
+    let portal = state
+        .db
+        .get_guest_portal_by_slug(slug)
+        .await
+        .map_err(|e| internal_error("Failed to get guest portal", e).into_response())?
+        .filter(|portal| portal.enabled)
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    if let (true, Some(ip)) = (rate_limit_service::config().enabled, client.ip_address.as_deref()) {
+        let limit = RateLimit::per_minute(portal.requests_per_minute as u32, portal.requests_per_minute as u32);
+        if let Err(retry_after) = rate_limit_service::take(&state.db, &format!("portal:{}:{}", portal.id, ip), &limit).await {
+            warn!("Rate limited guest portal {} for {}", portal.slug, ip);
+            return Err(rate_limit_service::too_many_requests(retry_after));
+        }
+    }
+
+    Ok(portal)
+}
+
+/// All guest portals (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/guest-portals",
+    tag = "guest_portals",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Guest portals", body = Vec<GuestPortal>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_guest_portals(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<GuestPortal>>, StatusCode> {
+    require_admin(&auth_user)?;
+    let portals = state
+        .db
+        .list_guest_portals()
+        .await
+        .map_err(|e| internal_error("Failed to list guest portals", e))?;
+    Ok(Json(portals))
+}
+
+/// Expose a label or collection read-only to visitors without an account (admin only)
+///
+/// Visitors find the portal at `/api/public/portals/{slug}`. They see the documents
+/// carrying the label, or those of the collection and its subcollections, and nothing
+/// about who owns them.
+#[utoipa::path(
+    post,
+    path = "/api/guest-portals",
+    tag = "guest_portals",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateGuestPortalRequest,
+    responses(
+        (status = 201, description = "Portal created", body = GuestPortal),
+        (status = 400, description = "Invalid slug, title or rate limit, or not exactly one of a label and a collection"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Label or collection not found"),
+        (status = 409, description = "Slug already used"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_guest_portal(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<CreateGuestPortalRequest>,
+) -> Result<(StatusCode, Json<GuestPortal>), StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected guest portal: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if !state
+        .db
+        .guest_portal_scope_exists(request.label_id, request.collection_id)
+        .await
+        .map_err(|e| internal_error("Failed to check guest portal scope", e))?
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if state
+        .db
+        .guest_portal_slug_taken(&request.slug)
+        .await
+        .map_err(|e| internal_error("Failed to check guest portal slug", e))?
+    {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let portal = state
+        .db
+        .create_guest_portal(&request, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to create guest portal", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::GUEST_PORTAL_CREATE, "guest_portal", Some(portal.id)).details(json!({
+            "slug": portal.slug,
+            "label_id": portal.label_id,
+            "collection_id": portal.collection_id,
+            "allow_download": portal.allow_download,
+            "show_text": portal.show_text,
+        })),
+    )
+    .await;
+    info!("User {} exposed guest portal {}", auth_user.user.username, portal.slug);
+
+    Ok((StatusCode::CREATED, Json(portal)))
+}
+
+/// A guest portal (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/guest-portals/{id}",
+    tag = "guest_portals",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Portal ID")
+    ),
+    responses(
+        (status = 200, description = "Guest portal", body = GuestPortal),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Portal not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_guest_portal(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GuestPortal>, StatusCode> {
+    require_admin(&auth_user)?;
+    let portal = state
+        .db
+        .get_guest_portal(id)
+        .await
+        .map_err(|e| internal_error("Failed to get guest portal", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(portal))
+}
+
+/// Change a guest portal, or disable it (admin only)
+#[utoipa::path(
+    patch,
+    path = "/api/guest-portals/{id}",
+    tag = "guest_portals",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Portal ID")
+    ),
+    request_body = UpdateGuestPortalRequest,
+    responses(
+        (status = 200, description = "Portal updated", body = GuestPortal),
+        (status = 400, description = "Invalid title, description or rate limit"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Portal not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_guest_portal(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateGuestPortalRequest>,
+) -> Result<Json<GuestPortal>, StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected guest portal update: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let portal = state
+        .db
+        .update_guest_portal(id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to update guest portal", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::GUEST_PORTAL_UPDATE, "guest_portal", Some(portal.id)).details(json!({
+            "slug": portal.slug,
+            "enabled": portal.enabled,
+            "allow_download": portal.allow_download,
+            "show_text": portal.show_text,
+            "requests_per_minute": portal.requests_per_minute,
+        })),
+    )
+    .await;
+
+    Ok(Json(portal))
+}
+
+/// Remove a guest portal; its documents are not affected (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/guest-portals/{id}",
+    tag = "guest_portals",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Portal ID")
+    ),
+    responses(
+        (status = 204, description = "Portal removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Portal not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_guest_portal(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+    if !state
+        .db
+        .delete_guest_portal(id)
+        .await
+        .map_err(|e| internal_error("Failed to delete guest portal", e))?
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::GUEST_PORTAL_DELETE, "guest_portal", Some(id)),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A portal's title, description and how many documents it holds
+#[utoipa::path(
+    get,
+    path = "/api/public/portals/{slug}",
+    tag = "guest_portals",
+    params(
+        ("slug" = String, Path, description = "Portal slug")
+    ),
+    responses(
+        (status = 200, description = "The portal", body = PublicGuestPortal),
+        (status = 404, description = "Unknown or disabled portal"),
+        (status = 429, description = "Too many requests from this address"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_public_portal(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Path(slug): Path<String>,
+) -> Result<Json<PublicGuestPortal>, Response> {
+    let portal = open_portal(&state, &client, &slug).await?;
+    let document_count = state
+        .db
+        .count_guest_portal_documents(&portal)
+        .await
+        .map_err(|e| internal_error("Failed to count guest portal documents", e).into_response())?;
+
+    Ok(Json(PublicGuestPortal::new(portal, document_count)))
+}
+
+/// Search the documents of a portal by file name, and by text when the portal shows it
+#[utoipa::path(
+    get,
+    path = "/api/public/portals/{slug}/documents",
+    tag = "guest_portals",
+    params(
+        ("slug" = String, Path, description = "Portal slug"),
+        GuestPortalSearchQuery
+    ),
+    responses(
+        (status = 200, description = "Matching documents, newest first", body = GuestPortalSearchResponse),
+        (status = 404, description = "Unknown or disabled portal"),
+        (status = 429, description = "Too many requests from this address"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn search_portal_documents(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Path(slug): Path<String>,
+    Query(query): Query<GuestPortalSearchQuery>,
+) -> Result<Json<GuestPortalSearchResponse>, Response> {
+    let portal = open_portal(&state, &client, &slug).await?;
+    let text = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let (documents, total) = state
+        .db
+        .search_guest_portal_documents(&portal, text, limit, offset)
+        .await
+        .map_err(|e| internal_error("Failed to search guest portal", e).into_response())?;
+
+    Ok(Json(GuestPortalSearchResponse { documents, total }))
+}
+
+/// A document of a portal, with its text when the portal shows it
+#[utoipa::path(
+    get,
+    path = "/api/public/portals/{slug}/documents/{id}",
+    tag = "guest_portals",
+    params(
+        ("slug" = String, Path, description = "Portal slug"),
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The document", body = GuestPortalDocumentDetail),
+        (status = 404, description = "Unknown or disabled portal, or a document it does not expose"),
+        (status = 429, description = "Too many requests from this address"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_portal_document(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Path((slug, id)): Path<(String, Uuid)>,
+) -> Result<Json<GuestPortalDocumentDetail>, Response> {
+    let portal = open_portal(&state, &client, &slug).await?;
+    let (document, text, _) = state
+        .db
+        .get_guest_portal_document(&portal, id)
+        .await
+        .map_err(|e| internal_error("Failed to get guest portal document", e).into_response())?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    Ok(Json(GuestPortalDocumentDetail {
+        document,
+        text: text.filter(|_| portal.show_text),
+    }))
+}
+
+/// Download a document of a portal that allows downloads
+#[utoipa::path(
+    get,
+    path = "/api/public/portals/{slug}/documents/{id}/download",
+    tag = "guest_portals",
+    params(
+        ("slug" = String, Path, description = "Portal slug"),
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The document", content_type = "application/octet-stream"),
+        (status = 403, description = "The portal does not allow downloads"),
+        (status = 404, description = "Unknown or disabled portal, or a document it does not expose"),
+        (status = 429, description = "Too many requests from this address"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn download_portal_document(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Path((slug, id)): Path<(String, Uuid)>,
+) -> Result<Response, Response> {
+    let portal = open_portal(&state, &client, &slug).await?;
+    if !portal.allow_download {
+        return Err(StatusCode::FORBIDDEN.into_response());
+    }
+    let (document, _, file_path) = state
+        .db
+        .get_guest_portal_document(&portal, id)
+        .await
+        .map_err(|e| internal_error("Failed to get guest portal document", e).into_response())?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    let mut file_data = state.file_service.read_file(&file_path).await.map_err(|e| {
+        error!("Failed to read document file {}: {}", document.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    let strip_policy = embedded_metadata::configured().map_err(|e| {
+        error!("Invalid metadata stripping configuration: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    if strip_policy.strips_shared() {
+        if let Some(stripped) = embedded_metadata::strip(&file_data, &document.mime_type) {
+            file_data = stripped;
+        }
+    }
+
+    audit_service::record(
+        &state.db,
+        None,
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_DOWNLOAD, "document", Some(document.id))
+            .details(json!({ "via": "guest_portal", "guest_portal_id": portal.id, "filename": document.filename })),
+    )
+    .await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, document.mime_type)
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", document.filename))
+        .header(header::CONTENT_LENGTH, file_data.len().to_string())
+        .header(header::CACHE_CONTROL, "no-store")
+        .header("X-Robots-Tag", "noindex")
+        .body(Body::from(file_data))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })
+}
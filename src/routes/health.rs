@@ -0,0 +1,197 @@
+use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::{services::llm::llm_service::LLMService, AppState};
+
+/// Each dependency gets this long to answer a probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The OCR worker loop goes round every few seconds; one silent for longer while it
+/// has free job slots is stuck
+const OCR_HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/live", get(liveness))
+        .route("/ready", get(readiness))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+    /// Not configured on this server, so not probed
+    Disabled,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    /// `database`, `storage`, `ocr_worker` or `llm`
+    pub name: String,
+    pub status: HealthStatus,
+    /// Whether the server is not ready while the component is down
+    pub required: bool,
+    /// How long the probe took
+    pub latency_ms: u64,
+    /// Why the component is down, or a note on its state
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub components: Vec<ComponentHealth>,
+}
+
+/// Whether the server process is running and answering requests
+///
+/// Dependencies are not probed, so an outage of the database does not get the
+/// server restarted.
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses(
+        (status = 200, description = "The server is running", body = HealthReport),
+    )
+)]
+pub async fn liveness() -> Json<HealthReport> {
+    Json(HealthReport { status: HealthStatus::Up, components: Vec::new() })
+}
+
+/// Whether the server can serve requests: the database answers, storage accepts
+/// writes and the OCR worker runs. A configured LLM endpoint is probed as well but
+/// does not make the server unready.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every required component is up", body = HealthReport),
+        (status = 503, description = "A required component is down", body = HealthReport),
+    )
+)]
+pub async fn readiness(State(state): State<Arc<AppState>>) -> (StatusCode, Json<HealthReport>) {
+    let (database, storage, ocr_worker, llm) = tokio::join!(
+        probe("database", true, async {
+            sqlx::query("SELECT 1")
+                .execute(state.db.get_pool())
+                .await
+                .map(|_| None)
+                .map_err(|e| e.to_string())
+        }),
+        probe("storage", true, async {
+            state.file_service.check_writable().await.map(|_| None).map_err(|e| e.to_string())
+        }),
+        probe("ocr_worker", true, async { ocr_worker_status(&state) }),
+        probe_llm(&state),
+    );
+
+    let report = health_report(vec![database, storage, ocr_worker, llm]);
+    let code = if report.status == HealthStatus::Up {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(report))
+}
+
+/// Run a probe within `PROBE_TIMEOUT`; it returns a note on success or why it failed
+async fn probe<F>(name: &str, required: bool, check: F) -> ComponentHealth
+where
+    F: Future<Output = Result<Option<String>, String>>,
+{
+    let started = Instant::now();
+    let result = match tokio::time::timeout(PROBE_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("No answer within {} seconds", PROBE_TIMEOUT.as_secs())),
+    };
+    let (status, message) = match result {
+        Ok(note) => (HealthStatus::Up, note),
+        Err(error) => (HealthStatus::Down, Some(error)),
+    };
+    ComponentHealth {
+        name: name.to_string(),
+        status,
+        required,
+        latency_ms: started.elapsed().as_millis() as u64,
+        message,
+    }
+}
+
+fn ocr_worker_status(state: &AppState) -> Result<Option<String>, String> {
+    let queue = &state.queue_service;
+    let Some(age) = queue.heartbeat_age() else {
+        return Err("The OCR worker has not started".to_string());
+    };
+    // A worker waiting for a free job slot does not go round its loop
+    if age > OCR_HEARTBEAT_TIMEOUT && queue.active_jobs() < queue.max_concurrent_jobs() {
+        return Err(format!("The OCR worker has not polled the queue for {} seconds", age.as_secs()));
+    }
+    let paused = queue.is_paused() || crate::services::maintenance_service::holds(crate::models::QueueKind::Ocr);
+    Ok(paused.then(|| "OCR processing is paused".to_string()))
+}
+
+async fn probe_llm(state: &AppState) -> ComponentHealth {
+    let llm = LLMService::new(state.db.get_pool().clone());
+    if !llm.configured() {
+        return ComponentHealth {
+            name: "llm".to_string(),
+            status: HealthStatus::Disabled,
+            required: false,
+            latency_ms: 0,
+            message: None,
+        };
+    }
+    probe("llm", false, async { llm.check_reachable(PROBE_TIMEOUT).await.map(|_| None) }).await
+}
+
+/// Up unless a required component is down
+fn health_report(components: Vec<ComponentHealth>) -> HealthReport {
+    let ready = components
+        .iter()
+        .all(|component| !component.required || component.status != HealthStatus::Down);
+    HealthReport {
+        status: if ready { HealthStatus::Up } else { HealthStatus::Down },
+        components,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(name: &str, status: HealthStatus, required: bool) -> ComponentHealth {
+        ComponentHealth { name: name.to_string(), status, required, latency_ms: 1, message: None }
+    }
+
+    #[test]
+    fn test_optional_components_do_not_fail_readiness() {
+        let report = health_report(vec![
+            component("database", HealthStatus::Up, true),
+            component("llm", HealthStatus::Down, false),
+        ]);
+        assert_eq!(report.status, HealthStatus::Up);
+
+        let report = health_report(vec![
+            component("database", HealthStatus::Down, true),
+            component("llm", HealthStatus::Disabled, false),
+        ]);
+        assert_eq!(report.status, HealthStatus::Down);
+    }
+
+    #[tokio::test]
+    async fn test_probe_reports_failures() {
+        let failed = probe("storage", true, async { Err("read-only file system".to_string()) }).await;
+        assert_eq!(failed.status, HealthStatus::Down);
+        assert_eq!(failed.message.as_deref(), Some("read-only file system"));
+
+        let paused = probe("ocr_worker", true, async { Ok(Some("OCR processing is paused".to_string())) }).await;
+        assert_eq!(paused.status, HealthStatus::Up);
+    }
+}
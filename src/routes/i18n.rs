@@ -0,0 +1,136 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::{
+    auth::AuthUser,
+    models::{LocaleInfo, ReloadCatalogsResponse, UpdateUserLocaleRequest, UserLocaleResponse},
+    routes::queue::require_admin,
+    services::i18n,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/locales", get(list_locales))
+        .route("/reload", post(reload_catalogs))
+}
+
+/// Locales notifications, digests and error messages can be written in
+#[utoipa::path(
+    get,
+    path = "/api/i18n/locales",
+    tag = "i18n",
+    responses(
+        (status = 200, description = "Locales with a translation catalog", body = Vec<LocaleInfo>)
+    )
+)]
+pub async fn list_locales() -> Json<Vec<LocaleInfo>> {
+    Json(i18n::available_locales())
+}
+
+/// Read the translation catalogs of `I18N_CATALOG_DIR` again (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/i18n/reload",
+    tag = "i18n",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Catalogs now in use, and files that were skipped", body = ReloadCatalogsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required")
+    )
+)]
+pub async fn reload_catalogs(auth_user: AuthUser) -> Result<Json<ReloadCatalogsResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    let errors = tokio::task::spawn_blocking(i18n::reload_catalogs).await.map_err(|e| {
+        error!("Failed to reload translation catalogs: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("User {} reloaded the translation catalogs", auth_user.user.username);
+
+    Ok(Json(ReloadCatalogsResponse {
+        locales: i18n::available_locales(),
+        errors,
+    }))
+}
+
+/// The locale of the current user
+#[utoipa::path(
+    get,
+    path = "/api/users/me/locale",
+    tag = "i18n",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The chosen and the effective locale", body = UserLocaleResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_my_locale(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<UserLocaleResponse>, StatusCode> {
+    let locale = state.db.get_user_locale(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to get the locale of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(UserLocaleResponse {
+        effective_locale: i18n::locale_of(&state.db, auth_user.user.id).await,
+        locale,
+    }))
+}
+
+/// Choose the locale of the current user's notifications, digests and error messages
+#[utoipa::path(
+    put,
+    path = "/api/users/me/locale",
+    tag = "i18n",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = UpdateUserLocaleRequest,
+    responses(
+        (status = 200, description = "Locale changed", body = UserLocaleResponse),
+        (status = 400, description = "No catalog for the locale"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_my_locale(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateUserLocaleRequest>,
+) -> Result<Json<UserLocaleResponse>, StatusCode> {
+    let locale = match request.locale.as_deref() {
+        Some(requested) => {
+            if i18n::resolve_locale(requested).is_none() {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            i18n::normalize_locale(requested)
+        }
+        None => None,
+    };
+
+    state.db.set_user_locale(auth_user.user.id, locale.as_deref()).await.map_err(|e| {
+        error!("Failed to set the locale of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    i18n::forget_user_locale(auth_user.user.id);
+
+    Ok(Json(UserLocaleResponse {
+        effective_locale: i18n::locale_of(&state.db, auth_user.user.id).await,
+        locale,
+    }))
+}
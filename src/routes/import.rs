@@ -0,0 +1,242 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{Import, UserRole},
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::import_service::ImportService,
+    AppState,
+};
+
+/// Imports listed per request
+const IMPORT_LIST_LIMIT: i64 = 50;
+const DEFAULT_IMPORT_MAX_SIZE_MB: usize = 10 * 1024;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/",
+            get(list_imports)
+                .post(create_import)
+                .layer(DefaultBodyLimit::max(import_max_size_bytes())),
+        )
+        .route("/{id}", get(get_import))
+}
+
+/// Largest archive that can be uploaded, from `IMPORT_MAX_SIZE_MB`
+fn import_max_size_bytes() -> usize {
+    std::env::var("IMPORT_MAX_SIZE_MB")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_IMPORT_MAX_SIZE_MB)
+        .saturating_mul(1024 * 1024)
+}
+
+async fn discard_upload(upload_path: &std::path::Path) {
+    if let Err(e) = tokio::fs::remove_file(upload_path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove import upload {}: {}", upload_path.display(), e);
+        }
+    }
+}
+
+fn parse_flag(value: &str) -> Result<bool, StatusCode> {
+    match value.trim() {
+        "true" | "1" => Ok(true),
+        "false" | "0" | "" => Ok(false),
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// Restore an export archive, uploaded as the multipart field `file`. The fields
+/// `recreate_users` (admins only) and `recreate_labels` take `true` or `false`.
+#[utoipa::path(
+    post,
+    path = "/api/import",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body(content = String, description = "Export archive as `file`, with optional `recreate_users` and `recreate_labels` fields", content_type = "multipart/form-data"),
+    responses(
+        (status = 202, description = "Import queued; follow it at /api/import/{id}", body = Import),
+        (status = 400, description = "No archive or invalid options"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Only admins can recreate users"),
+        (status = 413, description = "Archive larger than IMPORT_MAX_SIZE_MB"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_import(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Import>), StatusCode> {
+    let service = ImportService::new(state.clone());
+    let upload_path = service.archive_path(Uuid::new_v4()).with_extension("zip.partial");
+    let mut recreate_users = false;
+    let mut recreate_labels = false;
+    let mut uploaded = false;
+
+    let result: Result<(), StatusCode> = async {
+        while let Some(mut field) = multipart.next_field().await.map_err(|e| {
+            warn!("Failed to read import upload: {}", e);
+            StatusCode::BAD_REQUEST
+        })? {
+            let name = field.name().unwrap_or("").to_string();
+            match name.as_str() {
+                "recreate_users" => recreate_users = parse_flag(&field.text().await.map_err(|_| StatusCode::BAD_REQUEST)?)?,
+                "recreate_labels" => recreate_labels = parse_flag(&field.text().await.map_err(|_| StatusCode::BAD_REQUEST)?)?,
+                "file" => {
+                    let io_error = |e: std::io::Error| {
+                        error!("Failed to store import upload at {}: {}", upload_path.display(), e);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    };
+                    if let Some(dir) = upload_path.parent() {
+                        tokio::fs::create_dir_all(dir).await.map_err(io_error)?;
+                    }
+                    let mut file = tokio::fs::File::create(&upload_path).await.map_err(io_error)?;
+                    while let Some(chunk) = field.chunk().await.map_err(|e| {
+                        warn!("Import upload was interrupted: {}", e);
+                        StatusCode::BAD_REQUEST
+                    })? {
+                        file.write_all(&chunk).await.map_err(io_error)?;
+                    }
+                    file.flush().await.map_err(io_error)?;
+                    uploaded = true;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err(status) = result {
+        discard_upload(&upload_path).await;
+        return Err(status);
+    }
+    if !uploaded {
+        discard_upload(&upload_path).await;
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if recreate_users && auth_user.user.role != UserRole::Admin {
+        discard_upload(&upload_path).await;
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let import = match state.db.create_import(auth_user.user.id, recreate_users, recreate_labels).await {
+        Ok(import) => import,
+        Err(e) => {
+            error!("Failed to create import: {}", e);
+            discard_upload(&upload_path).await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    // Failing here leaves the import without an archive, so it fails when it runs
+    if let Err(e) = tokio::fs::rename(&upload_path, service.archive_path(import.id)).await {
+        error!("Failed to move the archive of import {} into place: {}", import.id, e);
+    }
+
+    info!("User {} started import {}", auth_user.user.id, import.id);
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LIBRARY_IMPORT, "import", Some(import.id))
+            .details(json!({ "recreate_users": recreate_users, "recreate_labels": recreate_labels })),
+    )
+    .await;
+
+    let import_id = import.id;
+    spawn_guarded(format!("import {}", import_id), async move {
+        if let Err(e) = service.run(import_id).await {
+            error!("Import {} stopped: {}", import_id, e);
+        }
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(import)))
+}
+
+/// The user's imports, newest first
+#[utoipa::path(
+    get,
+    path = "/api/import",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Recent imports with their progress", body = Vec<Import>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_imports(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<Import>>, StatusCode> {
+    let imports = state
+        .db
+        .list_imports(auth_user.user.id, IMPORT_LIST_LIMIT)
+        .await
+        .map_err(|e| {
+            error!("Failed to list imports: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(imports))
+}
+
+/// Status and progress of an import
+#[utoipa::path(
+    get,
+    path = "/api/import/{id}",
+    tag = "export",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Import ID")
+    ),
+    responses(
+        (status = 200, description = "Import", body = Import),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Import not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_import(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Import>, StatusCode> {
+    let import = state
+        .db
+        .get_import(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get import {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if import.user_id != auth_user.user.id && auth_user.user.role != UserRole::Admin {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(import))
+}
@@ -0,0 +1,259 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{CreateIngestionHookRequest, IngestionHook, IngestionHookKind, UpdateIngestionHookRequest},
+    routes::queue::require_admin,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::event_service,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_ingestion_hooks).post(create_ingestion_hook))
+        .route(
+            "/{id}",
+            get(get_ingestion_hook)
+                .put(update_ingestion_hook)
+                .delete(delete_ingestion_hook),
+        )
+}
+
+async fn load_hook(state: &AppState, id: Uuid) -> Result<IngestionHook, StatusCode> {
+    state
+        .db
+        .get_ingestion_hook(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get ingestion hook {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Every ingestion hook, pre-consume hooks first, in the order they run
+#[utoipa::path(
+    get,
+    path = "/api/ingestion-hooks",
+    tag = "ingestion_hooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Ingestion hooks", body = Vec<IngestionHook>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_ingestion_hooks(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<IngestionHook>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let hooks = state.db.list_ingestion_hooks().await.map_err(|e| {
+        error!("Failed to list ingestion hooks: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(hooks))
+}
+
+/// Register a command or webhook to run before files are stored or after their OCR.
+/// Webhook hooks get a secret their requests are signed with.
+#[utoipa::path(
+    post,
+    path = "/api/ingestion-hooks",
+    tag = "ingestion_hooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateIngestionHookRequest,
+    responses(
+        (status = 201, description = "Ingestion hook created", body = IngestionHook),
+        (status = 400, description = "Empty name, invalid target or timeout"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_ingestion_hook(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<CreateIngestionHookRequest>,
+) -> Result<(StatusCode, Json<IngestionHook>), StatusCode> {
+    require_admin(&auth_user)?;
+    request.validate().map_err(|e| {
+        debug!("Invalid ingestion hook: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let secret = (request.kind == IngestionHookKind::Webhook).then(event_service::generate_secret);
+    let hook = state
+        .db
+        .create_ingestion_hook(&request, secret.as_deref(), auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to create ingestion hook: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::INGESTION_HOOK_CREATE, "ingestion_hook", Some(hook.id)).details(json!({
+            "name": hook.name,
+            "stage": hook.stage,
+            "kind": hook.kind,
+            "target": hook.target,
+        })),
+    )
+    .await;
+
+    info!("User {} created {} ingestion hook '{}' ({})", auth_user.user.id, hook.stage, hook.name, hook.id);
+    Ok((StatusCode::CREATED, Json(hook)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ingestion-hooks/{id}",
+    tag = "ingestion_hooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Ingestion hook ID")
+    ),
+    responses(
+        (status = 200, description = "Ingestion hook, with the error of its last run", body = IngestionHook),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Ingestion hook not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_ingestion_hook(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<IngestionHook>, StatusCode> {
+    require_admin(&auth_user)?;
+    Ok(Json(load_hook(&state, id).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/ingestion-hooks/{id}",
+    tag = "ingestion_hooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Ingestion hook ID")
+    ),
+    request_body = UpdateIngestionHookRequest,
+    responses(
+        (status = 200, description = "Ingestion hook updated", body = IngestionHook),
+        (status = 400, description = "Empty name, invalid target or timeout"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Ingestion hook not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_ingestion_hook(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateIngestionHookRequest>,
+) -> Result<Json<IngestionHook>, StatusCode> {
+    require_admin(&auth_user)?;
+    let hook = load_hook(&state, id).await?;
+    request.validate(hook.kind).map_err(|e| {
+        debug!("Invalid ingestion hook update: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let hook = state
+        .db
+        .update_ingestion_hook(id, &request)
+        .await
+        .map_err(|e| {
+            error!("Failed to update ingestion hook {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::INGESTION_HOOK_UPDATE, "ingestion_hook", Some(hook.id)).details(json!({
+            "name": hook.name,
+            "target": hook.target,
+            "enabled": hook.enabled,
+        })),
+    )
+    .await;
+
+    Ok(Json(hook))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/ingestion-hooks/{id}",
+    tag = "ingestion_hooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Ingestion hook ID")
+    ),
+    responses(
+        (status = 204, description = "Ingestion hook deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Ingestion hook not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_ingestion_hook(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let deleted = state.db.delete_ingestion_hook(id).await.map_err(|e| {
+        error!("Failed to delete ingestion hook {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::INGESTION_HOOK_DELETE, "ingestion_hook", Some(id)),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,98 @@
+use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+
+use crate::{
+    auth::AuthUser,
+    models::{InstanceInfo, InstanceSettings, UpdateInstanceSettingsRequest, DEFAULT_INSTANCE_NAME},
+    routes::queue::require_admin,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::i18n,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/", get(get_instance).put(update_instance))
+}
+
+/// Name, look and default language of this instance, for the login page
+#[utoipa::path(
+    get,
+    path = "/api/instance",
+    tag = "instance",
+    responses(
+        (status = 200, description = "Instance branding with the built-in values filled in", body = InstanceInfo),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_instance(State(state): State<Arc<AppState>>) -> Result<Json<InstanceInfo>, StatusCode> {
+    let settings = state.db.get_instance_settings().await.map_err(|e| {
+        error!("Failed to get instance settings: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(InstanceInfo {
+        name: settings.name.unwrap_or_else(|| DEFAULT_INSTANCE_NAME.to_string()),
+        logo_url: settings.logo_url,
+        accent_color: settings.accent_color,
+        login_message: settings.login_message,
+        default_language: i18n::default_locale(),
+    }))
+}
+
+/// Replace the instance settings (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/instance",
+    tag = "instance",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = UpdateInstanceSettingsRequest,
+    responses(
+        (status = 200, description = "Settings saved", body = InstanceSettings),
+        (status = 400, description = "Invalid settings, or no catalog for the default language"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_instance(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<UpdateInstanceSettingsRequest>,
+) -> Result<Json<InstanceSettings>, StatusCode> {
+    require_admin(&auth_user)?;
+    let mut request = request.normalized();
+    if let Err(reason) = request.validate() {
+        debug!("Rejected instance settings: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Some(language) = &request.default_language {
+        request.default_language = Some(i18n::resolve_locale(language).ok_or(StatusCode::BAD_REQUEST)?);
+    }
+
+    let settings = state.db.set_instance_settings(&request, auth_user.user.id).await.map_err(|e| {
+        error!("Failed to save instance settings: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    i18n::set_instance_locale(settings.default_language.clone());
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::INSTANCE_SETTINGS_UPDATE, "instance", None).details(json!({
+            "name": settings.name,
+            "logo_url": settings.logo_url,
+            "accent_color": settings.accent_color,
+            "default_language": settings.default_language,
+        })),
+    )
+    .await;
+    info!("User {} changed the instance settings", auth_user.user.username);
+
+    Ok(Json(settings))
+}
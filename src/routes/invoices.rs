@@ -0,0 +1,253 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{Json, Response},
+    routing::get,
+    Router,
+};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        InvoiceExportQuery, InvoiceExtractionQuery, InvoiceExtractionResponse, SharePermission,
+        INVOICE_DOCUMENT_TYPE, INVOICE_EXTRACTION_MODES,
+    },
+    services::invoice_extraction_service::InvoiceExtractionService,
+    AppState,
+};
+
+const CSV_COLUMNS: [&str; 10] = [
+    "document_id", "filename", "vendor", "invoice_number", "invoice_date", "due_date", "currency", "net", "tax", "total",
+];
+const CSV_LINE_ITEM_COLUMNS: [&str; 4] = ["item_description", "item_quantity", "item_unit_price", "item_amount"];
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/export", get(export_invoices))
+}
+
+/// Extract vendor, number, dates, amounts and line items from an invoice into its
+/// `Invoice` custom fields
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/invoice/extract",
+    tag = "invoices",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        InvoiceExtractionQuery
+    ),
+    responses(
+        (status = 200, description = "Extracted invoice data and the document's custom fields", body = InvoiceExtractionResponse),
+        (status = 400, description = "Unknown mode, or `llm` without an LLM configured"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 422, description = "Document has no text yet"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn extract_invoice(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Query(query): Query<InvoiceExtractionQuery>,
+) -> Result<Json<InvoiceExtractionResponse>, StatusCode> {
+    let mode = query.mode.as_deref().unwrap_or("auto");
+    if !INVOICE_EXTRACTION_MODES.contains(&mode) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let service = InvoiceExtractionService::new(state.db.clone());
+    if mode == "llm" && !service.llm_available() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::Edit)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let has_text = document.ocr_text.as_deref().or(document.content.as_deref()).is_some_and(|t| !t.trim().is_empty());
+    if !has_text {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let response = service.extract(&document, mode).await.map_err(|e| {
+        warn!("Invoice extraction failed for document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(response))
+}
+
+/// Export the current user's invoices as CSV for accounting
+#[utoipa::path(
+    get,
+    path = "/api/invoices/export",
+    tag = "invoices",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(InvoiceExportQuery),
+    responses(
+        (status = 200, description = "CSV with one row per invoice, or per line item", content_type = "text/csv", body = String),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn export_invoices(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<InvoiceExportQuery>,
+) -> Result<Response, StatusCode> {
+    let internal_error = |e: anyhow::Error| {
+        error!("Failed to export invoices: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let invoice_type = state
+        .db
+        .get_document_types(auth_user.user.id)
+        .await
+        .map_err(internal_error)?
+        .into_iter()
+        .find(|document_type| document_type.name.eq_ignore_ascii_case(INVOICE_DOCUMENT_TYPE));
+    let mut invoices = match invoice_type {
+        Some(invoice_type) => state
+            .db
+            .get_documents_of_type(invoice_type.id, auth_user.user.id)
+            .await
+            .map_err(internal_error)?,
+        None => Vec::new(),
+    };
+
+    // Dates are stored as YYYY-MM-DD, so they compare as text
+    let from = query.from.map(|date| date.to_string());
+    let to = query.to.map(|date| date.to_string());
+    invoices.retain(|(_, _, fields)| {
+        let date = fields.get("invoice_date").and_then(Value::as_str);
+        from.as_deref().is_none_or(|from| date.is_some_and(|date| date >= from))
+            && to.as_deref().is_none_or(|to| date.is_some_and(|date| date <= to))
+    });
+    invoices.sort_by_key(|(_, _, fields)| fields.get("invoice_date").and_then(Value::as_str).map(str::to_string));
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+        .header(header::CONTENT_DISPOSITION, "attachment; filename=\"invoices.csv\"")
+        .body(Body::from(invoices_csv(&invoices, query.line_items)))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// The CSV of the invoices, with the line item columns and one row per item when asked.
+/// Amounts have two decimals and `net` is the total less the tax.
+fn invoices_csv(invoices: &[(Uuid, String, Map<String, Value>)], line_items: bool) -> String {
+    let mut header: Vec<&str> = CSV_COLUMNS.to_vec();
+    if line_items {
+        header.extend(CSV_LINE_ITEM_COLUMNS);
+    }
+    let mut csv = header.join(",");
+    csv.push_str("\r\n");
+
+    for (document_id, filename, fields) in invoices {
+        let text = |key: &str| fields.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+        let number = |key: &str| fields.get(key).and_then(Value::as_f64);
+        let net = number("total").zip(number("tax")).map(|(total, tax)| total - tax);
+
+        let row = [
+            document_id.to_string(),
+            filename.clone(),
+            text("vendor"),
+            text("invoice_number"),
+            text("invoice_date"),
+            text("due_date"),
+            text("currency"),
+            money(net),
+            money(number("tax")),
+            money(number("total")),
+        ]
+        .map(|value| csv_field(&value))
+        .join(",");
+
+        let items = fields.get("line_items").and_then(Value::as_array).filter(|items| !items.is_empty());
+        match items {
+            Some(items) if line_items => {
+                for item in items {
+                    let item_number = |key: &str| item.get(key).and_then(Value::as_f64);
+                    let description = item.get("description").and_then(Value::as_str).unwrap_or_default();
+                    csv.push_str(&format!(
+                        "{},{},{},{},{}\r\n",
+                        row,
+                        csv_field(description),
+                        item_number("quantity").map(|quantity| quantity.to_string()).unwrap_or_default(),
+                        money(item_number("unit_price")),
+                        money(item_number("amount"))
+                    ));
+                }
+            }
+            _ if line_items => csv.push_str(&format!("{},,,,\r\n", row)),
+            _ => csv.push_str(&format!("{}\r\n", row)),
+        }
+    }
+
+    csv
+}
+
+fn money(amount: Option<f64>) -> String {
+    amount.map(|amount| format!("{:.2}", amount)).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_invoices_csv() {
+        let fields = json!({
+            "vendor": "ACME, Inc.",
+            "invoice_number": "INV-7",
+            "invoice_date": "2024-05-15",
+            "total": 119.0,
+            "tax": 19.0,
+            "currency": "EUR",
+            "line_items": [
+                {"description": "Consulting", "quantity": 1.0, "unit_price": 60.0, "amount": 60.0},
+                {"description": "Travel", "quantity": 2.0, "unit_price": 20.0, "amount": 40.0}
+            ]
+        });
+        let invoices = [(Uuid::nil(), "invoice.pdf".to_string(), fields.as_object().cloned().unwrap())];
+
+        let csv = invoices_csv(&invoices, false);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].ends_with(",invoice.pdf,\"ACME, Inc.\",INV-7,2024-05-15,,EUR,100.00,19.00,119.00"));
+
+        let csv = invoices_csv(&invoices, true);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with(",item_amount"));
+        assert!(lines[2].ends_with(",Travel,2,20.00,40.00"));
+    }
+}
@@ -1,6 +1,6 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    extract::{OriginalUri, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::Json,
     routing::{get, post, put, delete},
     Router,
@@ -12,12 +12,23 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use sqlx::{FromRow, Row};
 
-use crate::{auth::AuthUser, AppState};
+use crate::{
+    auth::AuthUser,
+    models::{ApplyLabelRulesQuery, ApplyLabelRulesResponse, LabelRule, SetLabelRuleRequest, SharePermission},
+    utils::search_query,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::event_service::EventService,
+    services::label_rule_service::LabelRuleService,
+    utils::pagination::{Cursor, Page, PageRequest},
+    AppState,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Label {
     pub id: Uuid,
     pub user_id: Option<Uuid>, // nullable for system labels
+    /// The label this one is nested under, `None` at the top level
+    pub parent_id: Option<Uuid>,
     pub name: String,
     pub description: Option<String>,
     pub color: String,
@@ -35,6 +46,8 @@ pub struct Label {
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateLabel {
     pub name: String,
+    /// One of the user's labels to nest the new label under
+    pub parent_id: Option<Uuid>,
     pub description: Option<String>,
     #[serde(default = "default_color")]
     pub color: String,
@@ -49,12 +62,24 @@ fn default_color() -> String {
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateLabel {
     pub name: Option<String>,
+    /// The new parent, or `null` to move the label to the top level
+    #[serde(default, deserialize_with = "explicit_null")]
+    #[schema(value_type = Option<Uuid>)]
+    pub parent_id: Option<Option<Uuid>>,
     pub description: Option<String>,
     pub color: Option<String>,
     pub background_color: Option<String>,
     pub icon: Option<String>,
 }
 
+/// `Some(None)` for an explicit `null`, which a plain `Option<Option<_>>` reads as missing
+fn explicit_null<'de, D>(deserializer: D) -> Result<Option<Option<Uuid>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<Uuid>::deserialize(deserializer).map(Some)
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct LabelAssignment {
     pub label_ids: Vec<Uuid>,
@@ -64,6 +89,12 @@ pub struct LabelAssignment {
 pub struct LabelQuery {
     #[serde(default)]
     pub include_counts: bool,
+    /// Labels per page, at most 500; without it and `cursor` all labels are returned
+    pub limit: Option<i64>,
+    /// Cursor of the next page, from the `Link` header of the previous one
+    pub cursor: Option<String>,
+    /// Send `X-Total-Count` for paged requests, default true
+    pub include_total: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -78,6 +109,184 @@ fn default_bulk_mode() -> String {
     "replace".to_string()
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MergeLabelRequest {
+    /// The label that keeps the documents; the label merged into it is deleted
+    pub into_label_id: Uuid,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LabelMergeResponse {
+    /// The remaining label, with its new counts
+    pub label: Label,
+    /// Documents that got the label; documents that already had it are not counted
+    pub documents_moved: i64,
+    pub sources_moved: i64,
+    pub nested_labels_moved: i64,
+    /// Whether the merged label's rule moved over; a label keeps its own rule
+    pub rule_moved: bool,
+    /// Saved searches whose tag filter named the merged label
+    pub saved_searches_updated: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RenameLabelRequest {
+    pub name: String,
+    /// Only report what the rename would affect
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RenameLabelResponse {
+    pub label: Label,
+    pub usage: LabelUsage,
+    /// Another of the user's labels that already has the name; merge into it instead
+    pub conflicting_label_id: Option<Uuid>,
+    pub applied: bool,
+    /// Saved searches whose tag filter was changed to the new name
+    pub saved_searches_updated: i64,
+}
+
+/// Everything that refers to a label, to judge what renaming, merging or deleting it affects
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct LabelUsage {
+    pub document_count: i64,
+    pub source_count: i64,
+    pub nested_label_count: i64,
+    pub has_rule: bool,
+    pub retention_policy_count: i64,
+    pub share_count: i64,
+    pub guest_portal_count: i64,
+    /// Saved searches that name the label; they find it by name, not by ID
+    #[sqlx(skip)]
+    pub saved_searches: Vec<LabelSavedSearchUsage>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LabelSavedSearchUsage {
+    pub id: Uuid,
+    pub name: String,
+    /// The label is one of the search's tag filters, which follow renames and merges
+    pub in_tags: bool,
+    /// The search text has a `label:` filter for it, which has to be edited by hand
+    pub in_query: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct UnusedLabelsQuery {
+    /// Leave out labels created within this many days, default 0
+    pub min_age_days: Option<i64>,
+}
+
+/// Deepest nesting of labels, counting the top level
+pub const MAX_LABEL_DEPTH: i32 = 10;
+
+/// Checks that the user's label `parent_id` can become the parent of `label_id`, or of a
+/// new label when `None`: it may not be the label itself or one of its descendants, and
+/// the hierarchy may not get deeper than `MAX_LABEL_DEPTH`.
+async fn validate_parent(
+    state: &AppState,
+    user_id: Uuid,
+    parent_id: Uuid,
+    label_id: Option<Uuid>,
+) -> Result<(), StatusCode> {
+    // The parent and its ancestors, bounded in case the table already holds a cycle
+    let ancestors: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        WITH RECURSIVE ancestors AS (
+            SELECT id, parent_id, 1 AS depth FROM labels
+            WHERE id = $1 AND user_id = $2 AND is_system = FALSE
+            UNION ALL
+            SELECT l.id, l.parent_id, a.depth + 1 FROM labels l
+            JOIN ancestors a ON l.id = a.parent_id
+            WHERE a.depth <= $3
+        )
+        SELECT id FROM ancestors
+        "#
+    )
+    .bind(parent_id)
+    .bind(user_id)
+    .bind(MAX_LABEL_DEPTH)
+    .fetch_all(state.db.get_pool())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to load label ancestors: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if ancestors.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let subtree_depth = match label_id {
+        Some(label_id) => {
+            if ancestors.contains(&label_id) {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            sqlx::query_scalar::<_, i32>(
+                r#"
+                WITH RECURSIVE subtree AS (
+                    SELECT id, 1 AS depth FROM labels WHERE id = $1
+                    UNION ALL
+                    SELECT l.id, s.depth + 1 FROM labels l
+                    JOIN subtree s ON l.parent_id = s.id
+                    WHERE s.depth <= $2
+                )
+                SELECT COALESCE(MAX(depth), 1) FROM subtree
+                "#
+            )
+            .bind(label_id)
+            .bind(MAX_LABEL_DEPTH)
+            .fetch_one(state.db.get_pool())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load label descendants: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+        }
+        None => 1,
+    };
+
+    if ancestors.len() as i32 + subtree_depth > MAX_LABEL_DEPTH {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(())
+}
+
+/// Owner of a document the user owns or that is shared with them with at least `permission`
+async fn document_owner(
+    state: &AppState,
+    document_id: Uuid,
+    auth_user: &AuthUser,
+    permission: SharePermission,
+) -> Result<Uuid, StatusCode> {
+    let document = state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, permission)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to verify document access: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(document.user_id)
+}
+
+/// The editable fields of a label, as recorded in the audit log
+fn label_snapshot(label: &Label) -> serde_json::Value {
+    serde_json::json!({
+        "name": label.name,
+        "parent_id": label.parent_id,
+        "description": label.description,
+        "color": label.color,
+        "background_color": label.background_color,
+        "icon": label.icon,
+    })
+}
+
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(get_labels))
@@ -90,6 +299,12 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/documents/{document_id}/labels/{label_id}", post(add_document_label))
         .route("/documents/{document_id}/labels/{label_id}", delete(remove_document_label))
         .route("/bulk/documents", post(bulk_update_document_labels))
+        .route("/{id}/rule", get(get_label_rule).put(set_label_rule).delete(delete_label_rule))
+        .route("/rules/apply", post(apply_label_rules))
+        .route("/unused", get(get_unused_labels))
+        .route("/{id}/usage", get(get_label_usage))
+        .route("/{id}/rename", post(rename_label))
+        .route("/{id}/merge", post(merge_label))
 }
 
 #[utoipa::path(
@@ -99,48 +314,65 @@ pub fn router() -> Router<Arc<AppState>> {
     security(("bearer_auth" = [])),
     params(LabelQuery),
     responses(
-        (status = 200, description = "List of labels", body = Vec<Label>)
+        (status = 200, description = "Labels by name, with a `Link` to the next page when paged and `X-Total-Count`", body = Vec<Label>),
+        (status = 400, description = "Invalid cursor")
     )
 )]
 pub async fn get_labels(
     Query(query): Query<LabelQuery>,
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
-) -> Result<Json<Vec<Label>>, StatusCode> {
+    OriginalUri(uri): OriginalUri,
+) -> Result<(HeaderMap, Json<Vec<Label>>), StatusCode> {
     let user_id = auth_user.user.id;
+    let page = PageRequest::<String>::parse_optional(query.limit, 100, 500, query.cursor.as_deref(), query.include_total)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let (after_name, after_id) = page
+        .as_ref()
+        .and_then(|page| page.after.as_ref())
+        .map(|after| (after.key.clone(), after.id))
+        .unzip();
+    let fetch_limit = page.as_ref().map(PageRequest::fetch_limit);
 
     let labels = if query.include_counts {
         sqlx::query_as::<_, Label>(
             r#"
             SELECT 
-                l.id, l.user_id, l.name, l.description, l.color, 
+                l.id, l.user_id, l.parent_id, l.name, l.description, l.color, 
                 l.background_color, l.icon, l.is_system, l.created_at, l.updated_at,
                 COUNT(DISTINCT dl.document_id) as document_count,
                 COUNT(DISTINCT sl.source_id) as source_count
             FROM labels l
             LEFT JOIN document_labels dl ON l.id = dl.label_id
             LEFT JOIN source_labels sl ON l.id = sl.label_id
-            WHERE (l.user_id = $1 OR l.is_system = TRUE)
-            GROUP BY l.id, l.user_id, l.name, l.description, l.color, 
+            WHERE (l.user_id = $1 OR l.is_system = TRUE OR l.workspace_id = $2)
+              AND ($3::text IS NULL OR (l.name, l.id) > ($3, $4))
+            GROUP BY l.id, l.user_id, l.parent_id, l.name, l.description, l.color, 
                      l.background_color, l.icon, l.is_system, l.created_at, l.updated_at
-            ORDER BY l.name
+            ORDER BY l.name, l.id
+            LIMIT $5
             "#
         )
-        .bind(user_id)
     } else {
         sqlx::query_as::<_, Label>(
             r#"
             SELECT 
-                id, user_id, name, description, color, 
+                id, user_id, parent_id, name, description, color, 
                 background_color, icon, is_system, created_at, updated_at,
                 0::bigint as document_count, 0::bigint as source_count
             FROM labels
-            WHERE (user_id = $1 OR is_system = TRUE)
-            ORDER BY name
+            WHERE (user_id = $1 OR is_system = TRUE OR workspace_id = $2)
+              AND ($3::text IS NULL OR (name, id) > ($3, $4))
+            ORDER BY name, id
+            LIMIT $5
             "#
         )
-        .bind(user_id)
     }
+    .bind(user_id)
+    .bind(auth_user.workspace_id)
+    .bind(after_name)
+    .bind(after_id)
+    .bind(fetch_limit)
     .fetch_all(state.db.get_pool())
     .await
     .map_err(|e| {
@@ -148,7 +380,30 @@ pub async fn get_labels(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    Ok(Json(labels))
+    let page = match page {
+        Some(page) => {
+            let total = if page.include_total {
+                let total: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM labels WHERE (user_id = $1 OR is_system = TRUE OR workspace_id = $2)"
+                )
+                .bind(user_id)
+                .bind(auth_user.workspace_id)
+                .fetch_one(state.db.get_pool())
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to count labels: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+                Some(total)
+            } else {
+                None
+            };
+            Page::new(labels, page.limit, total, |label| Cursor::new(label.name.clone(), label.id))
+        }
+        None => Page::whole(labels),
+    };
+
+    Ok((page.headers(&uri), Json(page.items)))
 }
 
 #[utoipa::path(
@@ -159,12 +414,13 @@ pub async fn get_labels(
     request_body = CreateLabel,
     responses(
         (status = 201, description = "Label created successfully", body = Label),
-        (status = 400, description = "Invalid input or label already exists"),
+        (status = 400, description = "Invalid input, unknown parent label or nesting too deep"),
     )
 )]
 pub async fn create_label(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Json(payload): Json<CreateLabel>,
 ) -> Result<Json<Label>, StatusCode> {
     let user_id = auth_user.user.id;
@@ -185,12 +441,16 @@ pub async fn create_label(
         }
     }
 
+    if let Some(parent_id) = payload.parent_id {
+        validate_parent(&state, user_id, parent_id, None).await?;
+    }
+
     let label = sqlx::query_as::<_, Label>(
         r#"
-        INSERT INTO labels (user_id, name, description, color, background_color, icon)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO labels (user_id, name, description, color, background_color, icon, parent_id, workspace_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         RETURNING 
-            id, user_id, name, description, color, background_color, icon, 
+            id, user_id, parent_id, name, description, color, background_color, icon, 
             is_system, created_at, updated_at,
             0::bigint as document_count, 0::bigint as source_count
         "#
@@ -201,6 +461,8 @@ pub async fn create_label(
     .bind(payload.color)
     .bind(payload.background_color)
     .bind(payload.icon)
+    .bind(payload.parent_id)
+    .bind(auth_user.workspace_id)
     .fetch_one(state.db.get_pool())
     .await
     .map_err(|e| {
@@ -212,6 +474,14 @@ pub async fn create_label(
         }
     })?;
 
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LABEL_CREATE, "label", Some(label.id)).after(label_snapshot(&label)),
+    )
+    .await;
+
     Ok(Json(label))
 }
 
@@ -238,7 +508,7 @@ pub async fn get_label(
     let label = sqlx::query_as::<_, Label>(
         r#"
         SELECT 
-            l.id, l.user_id, l.name, l.description, l.color, 
+            l.id, l.user_id, l.parent_id, l.name, l.description, l.color, 
             l.background_color, l.icon, l.is_system, l.created_at, l.updated_at,
             COUNT(DISTINCT dl.document_id) as document_count,
             COUNT(DISTINCT sl.source_id) as source_count
@@ -246,7 +516,7 @@ pub async fn get_label(
         LEFT JOIN document_labels dl ON l.id = dl.label_id
         LEFT JOIN source_labels sl ON l.id = sl.label_id
         WHERE l.id = $1 AND (l.user_id = $2 OR l.is_system = TRUE)
-        GROUP BY l.id, l.user_id, l.name, l.description, l.color, 
+        GROUP BY l.id, l.user_id, l.parent_id, l.name, l.description, l.color, 
                  l.background_color, l.icon, l.is_system, l.created_at, l.updated_at
         "#
     )
@@ -277,13 +547,14 @@ pub async fn get_label(
     responses(
         (status = 200, description = "Label updated successfully", body = Label),
         (status = 404, description = "Label not found"),
-        (status = 400, description = "Invalid input"),
+        (status = 400, description = "Invalid input, or a parent that is unknown, nested under the label or too deep"),
     )
 )]
 pub async fn update_label(
     Path(label_id): Path<Uuid>,
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Json(payload): Json<UpdateLabel>,
 ) -> Result<Json<Label>, StatusCode> {
     let user_id = auth_user.user.id;
@@ -302,8 +573,15 @@ pub async fn update_label(
     }
 
     // Check if label exists and user has permission
-    let existing = sqlx::query(
-        "SELECT id FROM labels WHERE id = $1 AND user_id = $2 AND is_system = FALSE"
+    let existing = sqlx::query_as::<_, Label>(
+        r#"
+        SELECT
+            id, user_id, parent_id, name, description, color, background_color, icon,
+            is_system, created_at, updated_at,
+            0::bigint as document_count, 0::bigint as source_count
+        FROM labels
+        WHERE id = $1 AND user_id = $2 AND is_system = FALSE
+        "#
     )
     .bind(label_id)
     .bind(user_id)
@@ -312,13 +590,14 @@ pub async fn update_label(
     .map_err(|e| {
         tracing::error!("Failed to check label existence: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
 
-    if existing.is_none() {
-        return Err(StatusCode::NOT_FOUND);
+    if let Some(Some(parent_id)) = payload.parent_id {
+        validate_parent(&state, user_id, parent_id, Some(label_id)).await?;
     }
 
-    // Use COALESCE to update only provided fields
+    // Use COALESCE to update only provided fields; the parent changes when given, even to null
     let label = sqlx::query_as::<_, Label>(
         r#"
         UPDATE labels 
@@ -328,10 +607,11 @@ pub async fn update_label(
             color = COALESCE($4, color),
             background_color = COALESCE($5, background_color),
             icon = COALESCE($6, icon),
+            parent_id = CASE WHEN $7 THEN $8 ELSE parent_id END,
             updated_at = CURRENT_TIMESTAMP
         WHERE id = $1
         RETURNING 
-            id, user_id, name, description, color, background_color, icon, 
+            id, user_id, parent_id, name, description, color, background_color, icon, 
             is_system, created_at, updated_at,
             0::bigint as document_count, 0::bigint as source_count
         "#
@@ -342,6 +622,8 @@ pub async fn update_label(
     .bind(payload.color)
     .bind(payload.background_color)
     .bind(payload.icon)
+    .bind(payload.parent_id.is_some())
+    .bind(payload.parent_id.flatten())
     .fetch_one(state.db.get_pool())
     .await
     .map_err(|e| {
@@ -353,6 +635,15 @@ pub async fn update_label(
         }
     })?;
 
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LABEL_UPDATE, "label", Some(label.id))
+            .changes(&label_snapshot(&existing), &label_snapshot(&label)),
+    )
+    .await;
+
     Ok(Json(label))
 }
 
@@ -365,7 +656,7 @@ pub async fn update_label(
         ("id" = Uuid, Path, description = "Label ID")
     ),
     responses(
-        (status = 204, description = "Label deleted successfully"),
+        (status = 204, description = "Label deleted successfully, its nested labels move up a level"),
         (status = 404, description = "Label not found"),
     )
 )]
@@ -373,26 +664,66 @@ pub async fn delete_label(
     Path(label_id): Path<Uuid>,
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
 ) -> Result<StatusCode, StatusCode> {
     let user_id = auth_user.user.id;
 
-    let result = sqlx::query(
-        "DELETE FROM labels WHERE id = $1 AND user_id = $2 AND is_system = FALSE"
+    let mut tx = state.db.get_pool().begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Nested labels move up to the deleted label's parent
+    sqlx::query(
+        r#"
+        UPDATE labels
+        SET parent_id = (SELECT parent_id FROM labels WHERE id = $1 AND user_id = $2 AND is_system = FALSE)
+        WHERE parent_id = $1 AND user_id = $2
+        "#
     )
     .bind(label_id)
     .bind(user_id)
-    .execute(state.db.get_pool())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to move nested labels: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let deleted = sqlx::query_as::<_, Label>(
+        r#"
+        DELETE FROM labels
+        WHERE id = $1 AND user_id = $2 AND is_system = FALSE
+        RETURNING
+            id, user_id, parent_id, name, description, color, background_color, icon,
+            is_system, created_at, updated_at,
+            0::bigint as document_count, 0::bigint as source_count
+        "#
+    )
+    .bind(label_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| {
         tracing::error!("Failed to delete label: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    if result.rows_affected() == 0 {
-        Err(StatusCode::NOT_FOUND)
-    } else {
-        Ok(StatusCode::NO_CONTENT)
-    }
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LABEL_DELETE, "label", Some(deleted.id)).before(label_snapshot(&deleted)),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
 #[utoipa::path(
@@ -413,29 +744,13 @@ pub async fn get_document_labels(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
 ) -> Result<Json<Vec<Label>>, StatusCode> {
-    let user_id = auth_user.user.id;
-
-    // Verify document ownership
-    let doc = sqlx::query(
-        "SELECT id FROM documents WHERE id = $1 AND user_id = $2"
-    )
-    .bind(document_id)
-    .bind(user_id)
-    .fetch_optional(state.db.get_pool())
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to verify document ownership: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    if doc.is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    // Readers of a shared document see its labels too
+    document_owner(&state, document_id, &auth_user, SharePermission::View).await?;
 
     let labels = sqlx::query_as::<_, Label>(
         r#"
         SELECT 
-            l.id, l.user_id, l.name, l.description, l.color, 
+            l.id, l.user_id, l.parent_id, l.name, l.description, l.color, 
             l.background_color, l.icon, l.is_system, l.created_at, l.updated_at,
             0::bigint as document_count, 0::bigint as source_count
         FROM labels l
@@ -474,26 +789,13 @@ pub async fn update_document_labels(
     Path(document_id): Path<Uuid>,
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Json(payload): Json<LabelAssignment>,
 ) -> Result<Json<Vec<Label>>, StatusCode> {
     let user_id = auth_user.user.id;
 
-    // Verify document ownership
-    let doc = sqlx::query(
-        "SELECT id FROM documents WHERE id = $1 AND user_id = $2"
-    )
-    .bind(document_id)
-    .bind(user_id)
-    .fetch_optional(state.db.get_pool())
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to verify document ownership: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    if doc.is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    // Labels come from the document owner, who may have shared the document
+    let owner_id = document_owner(&state, document_id, &auth_user, SharePermission::Edit).await?;
 
     // Verify all labels exist and are accessible
     if !payload.label_ids.is_empty() {
@@ -501,7 +803,7 @@ pub async fn update_document_labels(
             "SELECT COUNT(*) as count FROM labels WHERE id = ANY($1) AND (user_id = $2 OR is_system = TRUE)"
         )
         .bind(&payload.label_ids)
-        .bind(user_id)
+        .bind(owner_id)
         .fetch_one(state.db.get_pool())
         .await
         .map_err(|e| {
@@ -521,6 +823,17 @@ pub async fn update_document_labels(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    let previous_label_ids: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT label_id FROM document_labels WHERE document_id = $1 ORDER BY label_id"
+    )
+    .bind(document_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to load existing labels: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
     // Remove existing labels
     sqlx::query(
         "DELETE FROM document_labels WHERE document_id = $1"
@@ -554,6 +867,17 @@ pub async fn update_document_labels(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_LABELS_CHANGE, "document", Some(document_id))
+            .before(serde_json::json!({ "label_ids": previous_label_ids }))
+            .after(serde_json::json!({ "label_ids": payload.label_ids })),
+    )
+    .await;
+    EventService::new(state.db.clone()).publish_labels_changed(document_id).await;
+
     // Return updated labels
     get_document_labels(Path(document_id), State(state), auth_user).await
 }
@@ -577,32 +901,19 @@ pub async fn add_document_label(
     Path((document_id, label_id)): Path<(Uuid, Uuid)>,
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
 ) -> Result<StatusCode, StatusCode> {
     let user_id = auth_user.user.id;
 
-    // Verify document ownership
-    let doc = sqlx::query(
-        "SELECT id FROM documents WHERE id = $1 AND user_id = $2"
-    )
-    .bind(document_id)
-    .bind(user_id)
-    .fetch_optional(state.db.get_pool())
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to verify document ownership: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    if doc.is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    // Labels come from the document owner, who may have shared the document
+    let owner_id = document_owner(&state, document_id, &auth_user, SharePermission::Edit).await?;
 
     // Verify label exists and is accessible
     let label = sqlx::query(
         "SELECT id FROM labels WHERE id = $1 AND (user_id = $2 OR is_system = TRUE)"
     )
     .bind(label_id)
-    .bind(user_id)
+    .bind(owner_id)
     .fetch_optional(state.db.get_pool())
     .await
     .map_err(|e| {
@@ -624,7 +935,18 @@ pub async fn add_document_label(
     .await;
 
     match result {
-        Ok(_) => Ok(StatusCode::CREATED),
+        Ok(_) => {
+            audit_service::record(
+                &state.db,
+                Some(&auth_user.user),
+                &client,
+                AuditEvent::new(audit_service::DOCUMENT_LABELS_CHANGE, "document", Some(document_id))
+                    .details(serde_json::json!({ "added": [label_id] })),
+            )
+            .await;
+            EventService::new(state.db.clone()).publish_labels_changed(document_id).await;
+            Ok(StatusCode::CREATED)
+        }
         Err(e) if e.to_string().contains("duplicate key") => Ok(StatusCode::OK), // Already assigned
         Err(e) => {
             tracing::error!("Failed to add document label: {}", e);
@@ -651,25 +973,10 @@ pub async fn remove_document_label(
     Path((document_id, label_id)): Path<(Uuid, Uuid)>,
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
 ) -> Result<StatusCode, StatusCode> {
-    let user_id = auth_user.user.id;
-
-    // Verify document ownership
-    let doc = sqlx::query(
-        "SELECT id FROM documents WHERE id = $1 AND user_id = $2"
-    )
-    .bind(document_id)
-    .bind(user_id)
-    .fetch_optional(state.db.get_pool())
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to verify document ownership: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    if doc.is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    // Users the document is shared with for editing may change its labels too
+    document_owner(&state, document_id, &auth_user, SharePermission::Edit).await?;
 
     let result = sqlx::query(
         "DELETE FROM document_labels WHERE document_id = $1 AND label_id = $2"
@@ -684,10 +991,20 @@ pub async fn remove_document_label(
     })?;
 
     if result.rows_affected() == 0 {
-        Err(StatusCode::NOT_FOUND)
-    } else {
-        Ok(StatusCode::NO_CONTENT)
+        return Err(StatusCode::NOT_FOUND);
     }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_LABELS_CHANGE, "document", Some(document_id))
+            .details(serde_json::json!({ "removed": [label_id] })),
+    )
+    .await;
+    EventService::new(state.db.clone()).publish_labels_changed(document_id).await;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
 #[utoipa::path(
@@ -704,6 +1021,7 @@ pub async fn remove_document_label(
 pub async fn bulk_update_document_labels(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
     Json(payload): Json<BulkUpdateRequest>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     let user_id = auth_user.user.id;
@@ -834,8 +1152,602 @@ pub async fn bulk_update_document_labels(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    let events = EventService::new(state.db.clone());
+    for document_id in &payload.document_ids {
+        audit_service::record(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::DOCUMENT_LABELS_CHANGE, "document", Some(*document_id))
+                .details(serde_json::json!({ "bulk": payload.mode, "label_ids": payload.label_ids })),
+        )
+        .await;
+        events.publish_labels_changed(*document_id).await;
+    }
+
     Ok(Json(serde_json::json!({
         "message": format!("Labels {}d successfully", payload.mode),
         "documents_updated": payload.document_ids.len()
     })))
-}
\ No newline at end of file
+}
+
+/// Whether the label is one of the user's own, which can carry a rule
+async fn own_label_exists(state: &AppState, label_id: Uuid, user_id: Uuid) -> Result<bool, StatusCode> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM labels WHERE id = $1 AND user_id = $2 AND is_system = FALSE)"
+    )
+    .bind(label_id)
+    .bind(user_id)
+    .fetch_one(state.db.get_pool())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to verify label: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/labels/{id}/rule",
+    tag = "labels",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Label ID")
+    ),
+    responses(
+        (status = 200, description = "The rule that applies the label automatically", body = LabelRule),
+        (status = 404, description = "Label not found or without a rule"),
+    )
+)]
+pub async fn get_label_rule(
+    Path(label_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<LabelRule>, StatusCode> {
+    let rule = state
+        .db
+        .get_label_rule(label_id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch label rule: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(rule))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/labels/{id}/rule",
+    tag = "labels",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Label ID")
+    ),
+    request_body = SetLabelRuleRequest,
+    responses(
+        (status = 200, description = "Rule created or replaced", body = LabelRule),
+        (status = 400, description = "No condition, too many values, or an invalid MIME type or regex"),
+        (status = 404, description = "Label not found"),
+    )
+)]
+pub async fn set_label_rule(
+    Path(label_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(payload): Json<SetLabelRuleRequest>,
+) -> Result<Json<LabelRule>, StatusCode> {
+    let user_id = auth_user.user.id;
+
+    if !own_label_exists(&state, label_id, user_id).await? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let payload = payload.normalize().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let rule = state
+        .db
+        .set_label_rule(label_id, user_id, &payload)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save label rule: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(rule))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/labels/{id}/rule",
+    tag = "labels",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Label ID")
+    ),
+    responses(
+        (status = 204, description = "Rule deleted; labels it applied stay"),
+        (status = 404, description = "Label not found or without a rule"),
+    )
+)]
+pub async fn delete_label_rule(
+    Path(label_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_label_rule(label_id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete label rule: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Apply the user's label rules to their existing documents
+#[utoipa::path(
+    post,
+    path = "/api/labels/rules/apply",
+    tag = "labels",
+    security(("bearer_auth" = [])),
+    params(ApplyLabelRulesQuery),
+    responses(
+        (status = 200, description = "Documents checked and labels added", body = ApplyLabelRulesResponse),
+    )
+)]
+pub async fn apply_label_rules(
+    Query(query): Query<ApplyLabelRulesQuery>,
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<ApplyLabelRulesResponse>, StatusCode> {
+    let (documents_checked, labels_assigned) = LabelRuleService::new(state.db.clone())
+        .backfill(Some(auth_user.user.id), query.label_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to apply label rules: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(ApplyLabelRulesResponse { documents_checked, labels_assigned }))
+}
+
+/// One of the user's own labels, which they can rename and merge
+async fn load_own_label(state: &AppState, label_id: Uuid, user_id: Uuid) -> Result<Label, StatusCode> {
+    sqlx::query_as::<_, Label>(
+        r#"
+        SELECT
+            id, user_id, parent_id, name, description, color, background_color, icon,
+            is_system, created_at, updated_at,
+            0::bigint as document_count, 0::bigint as source_count
+        FROM labels
+        WHERE id = $1 AND user_id = $2 AND is_system = FALSE
+        "#
+    )
+    .bind(label_id)
+    .bind(user_id)
+    .fetch_optional(state.db.get_pool())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to load label: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn label_usage(state: &AppState, label: &Label, user_id: Uuid) -> Result<LabelUsage, StatusCode> {
+    let internal_error = |e: sqlx::Error| {
+        tracing::error!("Failed to load the usage of label {}: {}", label.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let mut usage = sqlx::query_as::<_, LabelUsage>(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM document_labels WHERE label_id = $1) AS document_count,
+            (SELECT COUNT(*) FROM source_labels WHERE label_id = $1) AS source_count,
+            (SELECT COUNT(*) FROM labels WHERE parent_id = $1) AS nested_label_count,
+            EXISTS(SELECT 1 FROM label_rules WHERE label_id = $1) AS has_rule,
+            (SELECT COUNT(*) FROM retention_policies WHERE label_id = $1) AS retention_policy_count,
+            (SELECT COUNT(*) FROM label_shares WHERE label_id = $1) AS share_count,
+            (SELECT COUNT(*) FROM guest_portals WHERE label_id = $1) AS guest_portal_count
+        "#
+    )
+    .bind(label.id)
+    .fetch_one(state.db.get_pool())
+    .await
+    .map_err(internal_error)?;
+
+    let saved_searches = state.db.get_saved_searches(user_id).await.map_err(|e| {
+        tracing::error!("Failed to load saved searches: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let name = label.name.to_lowercase();
+    usage.saved_searches = saved_searches
+        .into_iter()
+        .filter_map(|saved| {
+            let in_tags = saved.search.tags.iter().flatten().any(|tag| tag.to_lowercase() == name);
+            let in_query = search_query::parse(&saved.search.query).is_ok_and(|query| query.mentions_label(&label.name));
+            (in_tags || in_query).then_some(LabelSavedSearchUsage { id: saved.id, name: saved.name, in_tags, in_query })
+        })
+        .collect();
+
+    Ok(usage)
+}
+
+/// Point the tag filters of the user's saved searches that name `old_name` at `new_name`
+async fn rename_saved_search_tags(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    old_name: &str,
+    new_name: &str,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE saved_searches
+        SET search = jsonb_set(search, '{tags}', (
+                SELECT COALESCE(jsonb_agg(DISTINCT CASE WHEN LOWER(tag) = LOWER($2) THEN $3 ELSE tag END), '[]'::jsonb)
+                FROM jsonb_array_elements_text(search->'tags') AS tag
+            )),
+            updated_at = NOW()
+        WHERE user_id = $1
+          AND jsonb_typeof(search->'tags') = 'array'
+          AND EXISTS (SELECT 1 FROM jsonb_array_elements_text(search->'tags') AS tag WHERE LOWER(tag) = LOWER($2))
+        "#
+    )
+    .bind(user_id)
+    .bind(old_name)
+    .bind(new_name)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(result.rows_affected() as i64)
+}
+
+/// What refers to a label: documents, sources, nested labels, its rule, retention
+/// policies, shares, guest portals and saved searches
+#[utoipa::path(
+    get,
+    path = "/api/labels/{id}/usage",
+    tag = "labels",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Label ID")
+    ),
+    responses(
+        (status = 200, description = "Usage of the label", body = LabelUsage),
+        (status = 404, description = "Label not found"),
+    )
+)]
+pub async fn get_label_usage(
+    Path(label_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<LabelUsage>, StatusCode> {
+    let label = load_own_label(&state, label_id, auth_user.user.id).await?;
+    Ok(Json(label_usage(&state, &label, auth_user.user.id).await?))
+}
+
+/// Rename a label, or with `dry_run` only preview what the new name affects
+#[utoipa::path(
+    post,
+    path = "/api/labels/{id}/rename",
+    tag = "labels",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Label ID")
+    ),
+    request_body = RenameLabelRequest,
+    responses(
+        (status = 200, description = "Renamed label, or the preview, with its usage", body = RenameLabelResponse),
+        (status = 400, description = "Empty name"),
+        (status = 404, description = "Label not found"),
+        (status = 409, description = "Another of the user's labels has the name"),
+    )
+)]
+pub async fn rename_label(
+    Path(label_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(payload): Json<RenameLabelRequest>,
+) -> Result<Json<RenameLabelResponse>, StatusCode> {
+    let user_id = auth_user.user.id;
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let existing = load_own_label(&state, label_id, user_id).await?;
+    let usage = label_usage(&state, &existing, user_id).await?;
+    let conflicting_label_id: Option<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM labels WHERE user_id = $1 AND name = $2 AND id <> $3"
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(label_id)
+    .fetch_optional(state.db.get_pool())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to check label names: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if payload.dry_run || name == existing.name {
+        return Ok(Json(RenameLabelResponse {
+            label: existing,
+            usage,
+            conflicting_label_id,
+            applied: false,
+            saved_searches_updated: 0,
+        }));
+    }
+    if conflicting_label_id.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let internal_error = |e: sqlx::Error| {
+        tracing::error!("Failed to rename label {}: {}", label_id, e);
+        if e.to_string().contains("duplicate key") {
+            StatusCode::CONFLICT
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    let mut tx = state.db.get_pool().begin().await.map_err(internal_error)?;
+    let label = sqlx::query_as::<_, Label>(
+        r#"
+        UPDATE labels SET name = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        RETURNING
+            id, user_id, parent_id, name, description, color, background_color, icon,
+            is_system, created_at, updated_at,
+            0::bigint as document_count, 0::bigint as source_count
+        "#
+    )
+    .bind(label_id)
+    .bind(name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    let saved_searches_updated = rename_saved_search_tags(&mut tx, user_id, &existing.name, name)
+        .await
+        .map_err(internal_error)?;
+    tx.commit().await.map_err(internal_error)?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LABEL_UPDATE, "label", Some(label.id))
+            .changes(&label_snapshot(&existing), &label_snapshot(&label)),
+    )
+    .await;
+
+    Ok(Json(RenameLabelResponse {
+        label,
+        usage,
+        conflicting_label_id: None,
+        applied: true,
+        saved_searches_updated,
+    }))
+}
+
+/// Merge a duplicate label into another: its documents, sources, nested labels, rule
+/// and saved-search tags move over, then it is deleted
+#[utoipa::path(
+    post,
+    path = "/api/labels/{id}/merge",
+    tag = "labels",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "ID of the duplicate label, which is deleted")
+    ),
+    request_body = MergeLabelRequest,
+    responses(
+        (status = 200, description = "The remaining label and what moved to it", body = LabelMergeResponse),
+        (status = 400, description = "A label cannot be merged into itself"),
+        (status = 404, description = "Label not found"),
+        (status = 409, description = "The duplicate is shared, exposed by a guest portal or used by a retention policy"),
+    )
+)]
+pub async fn merge_label(
+    Path(label_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(payload): Json<MergeLabelRequest>,
+) -> Result<Json<LabelMergeResponse>, StatusCode> {
+    let user_id = auth_user.user.id;
+    if payload.into_label_id == label_id {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let duplicate = load_own_label(&state, label_id, user_id).await?;
+    let target = load_own_label(&state, payload.into_label_id, user_id).await?;
+
+    // Moving these would widen who sees, or what deletes, the remaining label's documents
+    let usage = label_usage(&state, &duplicate, user_id).await?;
+    if usage.share_count > 0 || usage.guest_portal_count > 0 || usage.retention_policy_count > 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let internal_error = |e: sqlx::Error| {
+        tracing::error!("Failed to merge label {} into {}: {}", label_id, target.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let mut tx = state.db.get_pool().begin().await.map_err(internal_error)?;
+
+    let document_ids: Vec<Uuid> = sqlx::query_scalar("SELECT document_id FROM document_labels WHERE label_id = $1")
+        .bind(label_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+    let documents_moved = sqlx::query(
+        r#"
+        INSERT INTO document_labels (document_id, label_id)
+        SELECT document_id, $2 FROM document_labels WHERE label_id = $1
+        ON CONFLICT (document_id, label_id) DO NOTHING
+        "#
+    )
+    .bind(label_id)
+    .bind(target.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?
+    .rows_affected() as i64;
+    let sources_moved = sqlx::query(
+        r#"
+        INSERT INTO source_labels (source_id, label_id)
+        SELECT source_id, $2 FROM source_labels WHERE label_id = $1
+        ON CONFLICT (source_id, label_id) DO NOTHING
+        "#
+    )
+    .bind(label_id)
+    .bind(target.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?
+    .rows_affected() as i64;
+
+    // A target nested under the duplicate first takes the duplicate's place, so that
+    // moving the nested labels cannot make a cycle
+    sqlx::query(
+        r#"
+        WITH RECURSIVE ancestors AS (
+            SELECT id, parent_id, 1 AS depth FROM labels WHERE id = $2
+            UNION ALL
+            SELECT l.id, l.parent_id, a.depth + 1 FROM labels l
+            JOIN ancestors a ON l.id = a.parent_id
+            WHERE a.depth <= $3
+        )
+        UPDATE labels SET parent_id = $4
+        WHERE id = $2 AND EXISTS (SELECT 1 FROM ancestors WHERE id = $1)
+        "#
+    )
+    .bind(label_id)
+    .bind(target.id)
+    .bind(MAX_LABEL_DEPTH)
+    .bind(duplicate.parent_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?;
+    let nested_labels_moved = sqlx::query("UPDATE labels SET parent_id = $2 WHERE parent_id = $1")
+        .bind(label_id)
+        .bind(target.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .rows_affected() as i64;
+
+    let rule_moved = sqlx::query(
+        r#"
+        UPDATE label_rules SET label_id = $2, updated_at = NOW()
+        WHERE label_id = $1 AND NOT EXISTS (SELECT 1 FROM label_rules WHERE label_id = $2)
+        "#
+    )
+    .bind(label_id)
+    .bind(target.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(internal_error)?
+    .rows_affected()
+        > 0;
+    let saved_searches_updated = rename_saved_search_tags(&mut tx, user_id, &duplicate.name, &target.name)
+        .await
+        .map_err(internal_error)?;
+
+    sqlx::query("DELETE FROM labels WHERE id = $1")
+        .bind(label_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+    tx.commit().await.map_err(internal_error)?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LABEL_MERGE, "label", Some(duplicate.id))
+            .before(label_snapshot(&duplicate))
+            .details(serde_json::json!({
+                "into_label_id": target.id,
+                "documents_moved": documents_moved,
+                "sources_moved": sources_moved,
+                "nested_labels_moved": nested_labels_moved,
+            })),
+    )
+    .await;
+    let events = EventService::new(state.db.clone());
+    for document_id in document_ids {
+        events.publish_labels_changed(document_id).await;
+    }
+
+    let Json(label) = get_label(Path(target.id), State(state.clone()), auth_user).await?;
+    Ok(Json(LabelMergeResponse {
+        label,
+        documents_moved,
+        sources_moved,
+        nested_labels_moved,
+        rule_moved,
+        saved_searches_updated,
+    }))
+}
+
+/// The user's labels that nothing refers to: no documents, sources, nested labels,
+/// rule, retention policies, shares or guest portals
+#[utoipa::path(
+    get,
+    path = "/api/labels/unused",
+    tag = "labels",
+    security(("bearer_auth" = [])),
+    params(UnusedLabelsQuery),
+    responses(
+        (status = 200, description = "Unused labels, oldest first", body = Vec<Label>),
+        (status = 400, description = "Negative age"),
+    )
+)]
+pub async fn get_unused_labels(
+    Query(query): Query<UnusedLabelsQuery>,
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<Label>>, StatusCode> {
+    let min_age_days = query.min_age_days.unwrap_or(0);
+    if min_age_days < 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let labels = sqlx::query_as::<_, Label>(
+        r#"
+        SELECT
+            l.id, l.user_id, l.parent_id, l.name, l.description, l.color,
+            l.background_color, l.icon, l.is_system, l.created_at, l.updated_at,
+            0::bigint as document_count, 0::bigint as source_count
+        FROM labels l
+        WHERE l.user_id = $1 AND l.is_system = FALSE
+          AND l.created_at <= NOW() - make_interval(days => $2::int)
+          AND NOT EXISTS (SELECT 1 FROM document_labels WHERE label_id = l.id)
+          AND NOT EXISTS (SELECT 1 FROM source_labels WHERE label_id = l.id)
+          AND NOT EXISTS (SELECT 1 FROM labels nested WHERE nested.parent_id = l.id)
+          AND NOT EXISTS (SELECT 1 FROM label_rules WHERE label_id = l.id)
+          AND NOT EXISTS (SELECT 1 FROM retention_policies WHERE label_id = l.id)
+          AND NOT EXISTS (SELECT 1 FROM label_shares WHERE label_id = l.id)
+          AND NOT EXISTS (SELECT 1 FROM guest_portals WHERE label_id = l.id)
+        ORDER BY l.created_at, l.name
+        "#
+    )
+    .bind(auth_user.user.id)
+    .bind(min_age_days.min(i32::MAX as i64) as i32)
+    .fetch_all(state.db.get_pool())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch unused labels: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(labels))
+}
@@ -0,0 +1,436 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post},
+    Router,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        CreateLegalHold, LegalHold, LegalHoldDocumentsRequest, LegalHoldDocumentsResponse, LegalHoldManifest,
+        LegalHoldManifestQuery,
+    },
+    routes::queue::require_admin,
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        legal_hold_service::{self, LegalHoldService},
+    },
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_legal_holds).post(create_legal_hold))
+        .route("/{id}", get(get_legal_hold))
+        .route("/{id}/documents", post(add_documents))
+        .route("/{id}/documents/{document_id}", delete(remove_document))
+        .route("/{id}/release", post(release_legal_hold))
+        .route("/{id}/manifest", get(export_manifest))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Refuses with 423 Locked a change to a document on an active legal hold
+pub(crate) async fn ensure_not_held(state: &AppState, document_id: Uuid) -> Result<(), StatusCode> {
+    match legal_hold_service::ensure_not_held(&state.db, document_id).await {
+        Ok(()) => Ok(()),
+        Err(e) if legal_hold_service::is_held_error(&e) => {
+            debug!("{}", e);
+            Err(StatusCode::LOCKED)
+        }
+        Err(e) => Err(internal_error("Failed to check for legal holds", e)),
+    }
+}
+
+async fn load_hold(state: &AppState, hold_id: Uuid) -> Result<LegalHold, StatusCode> {
+    state
+        .db
+        .get_legal_hold(hold_id)
+        .await
+        .map_err(|e| internal_error("Failed to get legal hold", e))?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// All legal holds, active ones first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/legal-holds",
+    tag = "legal_holds",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Legal holds", body = Vec<LegalHold>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_legal_holds(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<LegalHold>>, StatusCode> {
+    require_admin(&auth_user)?;
+    let holds = state
+        .db
+        .list_legal_holds()
+        .await
+        .map_err(|e| internal_error("Failed to list legal holds", e))?;
+    Ok(Json(holds))
+}
+
+/// Place a legal hold, optionally on documents right away (admin only)
+///
+/// While the hold is active its documents cannot be deleted, renamed, given to
+/// another owner or have their contents replaced, by anyone, admins included.
+#[utoipa::path(
+    post,
+    path = "/api/legal-holds",
+    tag = "legal_holds",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateLegalHold,
+    responses(
+        (status = 201, description = "Legal hold placed", body = LegalHold),
+        (status = 400, description = "No name, or too many documents"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_legal_hold(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<CreateLegalHold>,
+) -> Result<(StatusCode, Json<LegalHold>), StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected legal hold: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let hold = state
+        .db
+        .create_legal_hold(request.name.trim(), request.reason.as_deref(), auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to create legal hold", e))?;
+
+    let mut added = Vec::new();
+    if !request.document_ids.is_empty() {
+        (added, _) = state
+            .db
+            .add_documents_to_legal_hold(hold.id, &request.document_ids, auth_user.user.id)
+            .await
+            .map_err(|e| internal_error("Failed to add documents to legal hold", e))?;
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LEGAL_HOLD_CREATE, "legal_hold", Some(hold.id)).details(serde_json::json!({
+            "name": hold.name,
+            "reason": hold.reason,
+            "document_ids": added,
+        })),
+    )
+    .await;
+
+    info!("Admin {} placed legal hold {} on {} documents", auth_user.user.id, hold.id, added.len());
+    let hold = load_hold(&state, hold.id).await?;
+    Ok((StatusCode::CREATED, Json(hold)))
+}
+
+/// A legal hold (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/legal-holds/{id}",
+    tag = "legal_holds",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Legal hold ID")
+    ),
+    responses(
+        (status = 200, description = "Legal hold", body = LegalHold),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Legal hold not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_legal_hold(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(hold_id): Path<Uuid>,
+) -> Result<Json<LegalHold>, StatusCode> {
+    require_admin(&auth_user)?;
+    Ok(Json(load_hold(&state, hold_id).await?))
+}
+
+/// Put documents on an active legal hold (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/legal-holds/{id}/documents",
+    tag = "legal_holds",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Legal hold ID")
+    ),
+    request_body = LegalHoldDocumentsRequest,
+    responses(
+        (status = 200, description = "Documents added, already held and not found", body = LegalHoldDocumentsResponse),
+        (status = 400, description = "No documents, or too many"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Legal hold not found"),
+        (status = 409, description = "The hold was released"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn add_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(hold_id): Path<Uuid>,
+    Json(request): Json<LegalHoldDocumentsRequest>,
+) -> Result<Json<LegalHoldDocumentsResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected documents for legal hold {}: {}", hold_id, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if !load_hold(&state, hold_id).await?.is_active() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let (added, already_held) = state
+        .db
+        .add_documents_to_legal_hold(hold_id, &request.document_ids, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to add documents to legal hold", e))?;
+
+    let found: HashSet<_> = added.iter().chain(&already_held).collect();
+    let mut not_found = Vec::new();
+    let mut seen = HashSet::new();
+    for document_id in &request.document_ids {
+        if !found.contains(document_id) && seen.insert(*document_id) {
+            not_found.push(*document_id);
+        }
+    }
+
+    if !added.is_empty() {
+        audit_service::record(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::LEGAL_HOLD_UPDATE, "legal_hold", Some(hold_id))
+                .details(serde_json::json!({ "added_document_ids": added })),
+        )
+        .await;
+    }
+
+    Ok(Json(LegalHoldDocumentsResponse { added, already_held, not_found }))
+}
+
+/// Take a document off a legal hold (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/legal-holds/{id}/documents/{document_id}",
+    tag = "legal_holds",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Legal hold ID"),
+        ("document_id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 204, description = "Document taken off the hold"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "The document is not on the hold"),
+        (status = 409, description = "The hold was released; its documents are kept as a record"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn remove_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path((hold_id, document_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+    if !load_hold(&state, hold_id).await?.is_active() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let removed = state
+        .db
+        .remove_document_from_legal_hold(hold_id, document_id)
+        .await
+        .map_err(|e| internal_error("Failed to remove document from legal hold", e))?;
+    if !removed {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LEGAL_HOLD_UPDATE, "legal_hold", Some(hold_id))
+            .details(serde_json::json!({ "removed_document_id": document_id })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Release a legal hold so its documents can be changed again (admin only)
+///
+/// The hold and its list of documents are kept, so its manifest can still be exported.
+#[utoipa::path(
+    post,
+    path = "/api/legal-holds/{id}/release",
+    tag = "legal_holds",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Legal hold ID")
+    ),
+    responses(
+        (status = 200, description = "Released legal hold", body = LegalHold),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Legal hold not found"),
+        (status = 409, description = "Already released"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn release_legal_hold(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(hold_id): Path<Uuid>,
+) -> Result<Json<LegalHold>, StatusCode> {
+    require_admin(&auth_user)?;
+    load_hold(&state, hold_id).await?;
+
+    let released = state
+        .db
+        .release_legal_hold(hold_id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to release legal hold", e))?;
+    if !released {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let hold = load_hold(&state, hold_id).await?;
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LEGAL_HOLD_RELEASE, "legal_hold", Some(hold_id))
+            .details(serde_json::json!({ "name": hold.name, "document_count": hold.document_count })),
+    )
+    .await;
+
+    info!("Admin {} released legal hold {}", auth_user.user.id, hold_id);
+    Ok(Json(hold))
+}
+
+/// Export the integrity manifest of a legal hold (admin only)
+///
+/// Lists every stored file of the held documents, current contents and earlier
+/// versions, with its SHA-256 and timestamps. With `verify=true` each file is read
+/// back from storage and checked against its recorded hash. The CSV carries the
+/// manifest hash in the `X-Manifest-SHA256` header.
+#[utoipa::path(
+    get,
+    path = "/api/legal-holds/{id}/manifest",
+    tag = "legal_holds",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Legal hold ID"),
+        LegalHoldManifestQuery
+    ),
+    responses(
+        (status = 200, description = "Manifest as JSON, or as CSV with `format=csv`", body = LegalHoldManifest),
+        (status = 400, description = "Unknown format"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Legal hold not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn export_manifest(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(hold_id): Path<Uuid>,
+    Query(query): Query<LegalHoldManifestQuery>,
+) -> Result<Response, StatusCode> {
+    require_admin(&auth_user)?;
+    let csv = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
+    let hold = load_hold(&state, hold_id).await?;
+
+    let manifest = LegalHoldService::new(state.db.clone(), state.file_service.as_ref().clone())
+        .manifest(hold, auth_user.user.id, query.verify)
+        .await
+        .map_err(|e| internal_error("Failed to build legal hold manifest", e))?;
+
+    let failed = manifest.entries.iter().filter(|entry| entry.verified == Some(false)).count();
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::LEGAL_HOLD_MANIFEST_EXPORT, "legal_hold", Some(hold_id)).details(
+            serde_json::json!({
+                "manifest_hash": manifest.manifest_hash,
+                "files": manifest.entries.len(),
+                "verified": query.verify,
+                "verification_failures": failed,
+            }),
+        ),
+    )
+    .await;
+
+    if !csv {
+        return Ok(Json(manifest).into_response());
+    }
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"legal-hold-{}-manifest.csv\"", hold_id),
+        )
+        .header("X-Manifest-SHA256", &manifest.manifest_hash)
+        .body(Body::from(legal_hold_service::manifest_csv(&manifest.entries)))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
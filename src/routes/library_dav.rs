@@ -0,0 +1,176 @@
+//! The read-only WebDAV share of the document library, for mounting it in Finder or
+//! Explorer. Requests are dispatched on their method here since WebDAV adds methods
+//! the router does not know, such as PROPFIND.
+
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, OriginalUri, Request, State},
+    http::{
+        header::{ALLOW, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderValue, Method, Response, StatusCode,
+    },
+    response::IntoResponse,
+    routing::any,
+    Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    auth::BasicAuthUser,
+    routes::documents::crud::{file_part_response, open_file_part, range_headers, with_validators},
+    services::audit_service::{self, document_details, AuditEvent, ClientInfo},
+    services::library_dav_service::{self as library, DavPath},
+    utils::conditional::{self, Validators},
+    AppState,
+};
+
+/// Methods of the share; writing ones are refused
+const ALLOWED_METHODS: &str = "OPTIONS, GET, HEAD, PROPFIND";
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(library::DAV_ROOT, any(library_dav))
+        .route(&format!("{}/", library::DAV_ROOT), any(library_dav))
+        .route(&format!("{}/{{*path}}", library::DAV_ROOT), any(library_dav))
+}
+
+async fn library_dav(State(state): State<Arc<AppState>>, request: Request) -> Response<Body> {
+    let (mut parts, _) = request.into_parts();
+    // Clients ask which methods there are before they authenticate
+    if parts.method == Method::OPTIONS {
+        return options();
+    }
+
+    let auth_user = match BasicAuthUser::from_request_parts(&mut parts, &state).await {
+        Ok(BasicAuthUser(auth_user)) => auth_user,
+        Err(response) => return response,
+    };
+    let path = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.path().to_string())
+        .unwrap_or_else(|| parts.uri.path().to_string());
+    let Some(path) = DavPath::parse(&path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let result = match parts.method.as_str() {
+        "PROPFIND" => {
+            // Depth: infinity is answered as 1; clients walk the tree themselves
+            let depth = match parts.headers.get("depth").and_then(|value| value.to_str().ok()) {
+                Some("0") => 0,
+                _ => 1,
+            };
+            propfind(&state, auth_user.user.id, &path, depth).await
+        }
+        "GET" | "HEAD" => {
+            let client = ClientInfo::from_request_parts(&mut parts, &state).await.unwrap_or_default();
+            get_file(&state, &auth_user.user, &client, &path, &parts.method, parts.headers).await
+        }
+        _ => Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(ALLOW, ALLOWED_METHODS)
+            .body(Body::empty())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    result.unwrap_or_else(IntoResponse::into_response)
+}
+
+fn options() -> Response<Body> {
+    let mut response = StatusCode::OK.into_response();
+    let headers = response.headers_mut();
+    headers.insert("dav", HeaderValue::from_static("1"));
+    headers.insert(ALLOW, HeaderValue::from_static(ALLOWED_METHODS));
+    // Windows only mounts shares that announce themselves this way
+    headers.insert("ms-author-via", HeaderValue::from_static("DAV"));
+    response
+}
+
+async fn propfind(state: &AppState, user_id: uuid::Uuid, path: &DavPath, depth: u8) -> Result<Response<Body>, StatusCode> {
+    let entries = library::list(&state.db, user_id, path, depth)
+        .await
+        .map_err(|e| {
+            error!("Failed to list WebDAV library path {:?}: {}", path, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(library::multistatus(&entries)))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn get_file(
+    state: &AppState,
+    user: &crate::models::User,
+    client: &ClientInfo,
+    path: &DavPath,
+    method: &Method,
+    headers: axum::http::HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    let DavPath::File { grouping, folder, name } = path else {
+        // Folders have no content; file managers list them with PROPFIND
+        return Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(ALLOW, "OPTIONS, PROPFIND")
+            .body(Body::empty())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+    };
+    let file = library::find_file(&state.db, user.id, *grouping, folder.as_deref(), name)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up WebDAV library file {:?}: {}", path, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let document = state
+        .db
+        .get_document_by_id(file.id, user.id, user.role)
+        .await
+        .map_err(|e| {
+            error!("Database error getting document {}: {}", file.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let validators = Validators::for_content(document.file_hash.as_deref(), None, document.updated_at);
+    if validators.is_not_modified(&headers) {
+        return validators.not_modified(conditional::REVALIDATE);
+    }
+
+    if method == Method::HEAD {
+        let mut response = Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, &document.mime_type)
+            .header(CONTENT_LENGTH, document.file_size.to_string())
+            .header(CACHE_CONTROL, conditional::REVALIDATE)
+            .body(Body::empty())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        validators.write(response.headers_mut());
+        return Ok(response);
+    }
+
+    let headers = range_headers(headers, &validators);
+    let part = open_file_part(&state.file_service, &document.file_path, &headers)
+        .await
+        .map_err(|e| {
+            error!("Failed to read document file {}: {}", document.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if part.has_content() {
+        audit_service::record_file_access(
+            &state.db,
+            Some(user),
+            client,
+            AuditEvent::new(audit_service::DOCUMENT_DOWNLOAD, "document", Some(document.id))
+                .details(json!({ "via": "webdav", "document": document_details(&document) })),
+        )
+        .await;
+    }
+
+    Ok(with_validators(file_part_response(part, &document.mime_type, None)?, &validators))
+}
@@ -1,73 +1,574 @@
 use axum::{
-    extract::{Path, State},
-    Json, Router, routing::{get, post},
+    extract::{Path, Query, State},
+    Json, Router, routing::{get, patch, post, delete},
     http::StatusCode,
 };
 use serde_json::json;
+use tracing::{debug, error, info};
 use uuid::Uuid;
-use crate::services::llm::llm_service::LLMService;
+use crate::auth::AuthUser;
+use crate::models::{
+    AnalyzeDocumentQuery, ClearLlmDebugRunsQuery, ClearLlmDebugRunsResponse, DocumentGraph, DocumentGraphQuery,
+    EventType, GraphChange, GraphCorrection, GraphVersion, InvalidateLlmCacheQuery, InvalidateLlmCacheResponse,
+    ListLlmDebugRunsQuery, LlmCacheStats, LlmDebugLogStatus, LlmDebugRun, LlmDebugRunDetail, QueueKind,
+    RenameGraphNodeRequest,
+};
+use crate::routes::queue::require_admin;
+use crate::services::event_service::EventService;
+use crate::services::llm::{debug_log, llm_service::LLMService};
+use crate::services::maintenance_service;
 use crate::AppState;
 use std::sync::Arc;
-use sqlx::Row;
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/:id/analyze", post(analyze_document))
-        .route("/:id/graph", get(get_document_graph))
+        .route("/cache", get(get_llm_cache_stats).delete(invalidate_llm_cache))
+        .route("/debug", get(get_llm_debug_log_status))
+        .route("/debug/runs", get(list_llm_debug_runs).delete(clear_llm_debug_runs))
+        .route("/debug/runs/{run_id}", get(get_llm_debug_run))
+        .route("/{id}/analyze", post(analyze_document))
+        .route("/{id}/graph", get(get_document_graph))
+        .route("/{id}/graph/versions", get(list_graph_versions))
+        .route("/{id}/graph/nodes/{node_id}", patch(rename_graph_node).delete(delete_graph_node))
+        .route("/{id}/graph/edges/{edge_id}", delete(delete_graph_edge))
+        .route("/{id}/graph/corrections", get(list_graph_corrections))
+        .route("/{id}/graph/corrections/{correction_id}", delete(delete_graph_correction))
+}
+
+/// Fails with 404 unless the user can see the document
+async fn ensure_document_access(state: &AppState, auth_user: &AuthUser, id: Uuid) -> Result<(), StatusCode> {
+    state
+        .db
+        .get_document_by_id(id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Failed to get document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(())
 }
 
+/// Extract the document's knowledge graph with the LLM, as a new graph version
+///
+/// Text the model already answered for with the current prompt is answered from the
+/// cache unless `bypass_cache` is set. With `debug`, admins have the run's prompt and
+/// answer kept in the LLM debug log.
+#[utoipa::path(
+    post,
+    path = "/api/llm/{id}/analyze",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        AnalyzeDocumentQuery
+    ),
+    responses(
+        (status = 200, description = "Extracted entities and relationships"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Debug logging asked for by a user who is not an admin"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Analysis failed"),
+        (status = 503, description = "Analysis is paused")
+    )
+)]
 async fn analyze_document(
     State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
     Path(id): Path<Uuid>,
+    Query(query): Query<AnalyzeDocumentQuery>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let service = LLMService::new(state.db.get_pool().clone());
+    ensure_document_access(&state, &auth_user, id).await.map_err(|status| (status, String::new()))?;
+    if query.debug {
+        require_admin(&auth_user).map_err(|status| (status, String::new()))?;
+    }
+    if maintenance_service::holds(QueueKind::Analysis) {
+        return Err((StatusCode::SERVICE_UNAVAILABLE, "Analysis is paused".to_string()));
+    }
+
+    let mut service = LLMService::new(state.db.get_pool().clone());
+    if query.bypass_cache {
+        service = service.bypass_cache();
+    }
+    if query.debug {
+        service = service.debug_log();
+    }
     let result = service.analyze_document(id).await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    EventService::new(state.db.clone())
+        .publish_best_effort(
+            EventType::AnalysisCompleted,
+            None,
+            Some(id),
+            json!({
+                "document_id": id,
+                "node_count": result.nodes.len(),
+                "edge_count": result.edges.len(),
+            }),
+        )
+        .await;
+
     Ok(Json(json!(result)))
 }
 
+/// Cached LLM answers per prompt version and model (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/llm/cache",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Cache entries and hits per prompt version and model", body = Vec<LlmCacheStats>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_llm_cache_stats(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<LlmCacheStats>>, StatusCode> {
+    require_admin(&auth_user)?;
+    let stats = state.db.get_llm_cache_stats().await.map_err(|e| {
+        error!("Failed to get LLM cache statistics: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(stats))
+}
+
+/// Remove cached LLM answers of a prompt, e.g. of a version whose answers were poor (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/llm/cache",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(InvalidateLlmCacheQuery),
+    responses(
+        (status = 200, description = "Number of cached answers removed", body = InvalidateLlmCacheResponse),
+        (status = 400, description = "No task, or an invalid prompt version"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn invalidate_llm_cache(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<InvalidateLlmCacheQuery>,
+) -> Result<Json<InvalidateLlmCacheResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(e) = query.validate() {
+        debug!("Rejected LLM cache invalidation: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let deleted = state.db.invalidate_llm_cache(&query).await.map_err(|e| {
+        error!("Failed to invalidate LLM cache: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!(
+        "User {} removed {} cached LLM answers of task '{}' (version {:?})",
+        auth_user.user.username, deleted, query.task.trim(), query.prompt_version
+    );
+    Ok(Json(InvalidateLlmCacheResponse { deleted }))
+}
+
+/// How the LLM debug log is configured and how many runs it holds (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/llm/debug",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Debug log settings", body = LlmDebugLogStatus),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_llm_debug_log_status(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<LlmDebugLogStatus>, StatusCode> {
+    require_admin(&auth_user)?;
+    let runs = state.db.count_llm_debug_runs().await.map_err(|e| {
+        error!("Failed to count LLM debug runs: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let config = &*debug_log::CONFIG;
+    Ok(Json(LlmDebugLogStatus {
+        enabled: config.enabled,
+        redacted_kinds: config.redact.clone(),
+        retention_days: config.retention_days,
+        runs,
+    }))
+}
+
+/// Analysis runs in the LLM debug log, newest first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/llm/debug/runs",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(ListLlmDebugRunsQuery),
+    responses(
+        (status = 200, description = "Logged runs", body = Vec<LlmDebugRun>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_llm_debug_runs(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<ListLlmDebugRunsQuery>,
+) -> Result<Json<Vec<LlmDebugRun>>, StatusCode> {
+    require_admin(&auth_user)?;
+    let runs = state.db.list_llm_debug_runs(&query).await.map_err(|e| {
+        error!("Failed to list LLM debug runs: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(runs))
+}
+
+/// A logged analysis run with the prompts it sent and the answers it got (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/llm/debug/runs/{run_id}",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("run_id" = uuid::Uuid, Path, description = "Debug run ID")
+    ),
+    responses(
+        (status = 200, description = "The run and its requests", body = LlmDebugRunDetail),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Run not found, or removed after the retention period"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_llm_debug_run(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<LlmDebugRunDetail>, StatusCode> {
+    require_admin(&auth_user)?;
+    let run = state
+        .db
+        .get_llm_debug_run(run_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get LLM debug run {}: {}", run_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(run))
+}
+
+/// Remove logged runs, of one document or all (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/llm/debug/runs",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(ClearLlmDebugRunsQuery),
+    responses(
+        (status = 200, description = "Number of runs removed", body = ClearLlmDebugRunsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn clear_llm_debug_runs(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<ClearLlmDebugRunsQuery>,
+) -> Result<Json<ClearLlmDebugRunsResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    let deleted = state.db.clear_llm_debug_runs(query.document_id).await.map_err(|e| {
+        error!("Failed to clear LLM debug runs: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("User {} removed {} LLM debug runs", auth_user.user.username, deleted);
+    Ok(Json(ClearLlmDebugRunsResponse { deleted }))
+}
+
+async fn load_graph(state: &AppState, id: Uuid, version: Option<i32>) -> Result<DocumentGraph, StatusCode> {
+    state.db.get_document_graph(id, version).await.map_err(|e| {
+        error!("Failed to load knowledge graph of document {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// The document's knowledge graph, with corrections applied
+#[utoipa::path(
+    get,
+    path = "/api/llm/{id}/graph",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        DocumentGraphQuery
+    ),
+    responses(
+        (status = 200, description = "Graph version with corrections applied; empty if the document was never analyzed", body = DocumentGraph),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or version not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
 async fn get_document_graph(
     State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
     Path(id): Path<Uuid>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let pool = state.db.get_pool();
+    Query(query): Query<DocumentGraphQuery>,
+) -> Result<Json<DocumentGraph>, StatusCode> {
+    ensure_document_access(&state, &auth_user, id).await?;
+    let graph = load_graph(&state, id, query.version).await?;
+    if query.version.is_some() && graph.version.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(graph))
+}
+
+/// The document's graph versions, newest first
+#[utoipa::path(
+    get,
+    path = "/api/llm/{id}/graph/versions",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Graph versions", body = Vec<GraphVersion>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_graph_versions(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<GraphVersion>>, StatusCode> {
+    ensure_document_access(&state, &auth_user, id).await?;
+    let versions = state.db.list_graph_versions(id).await.map_err(|e| {
+        error!("Failed to list graph versions of document {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(versions))
+}
+
+/// Record a correction of the latest graph and return the corrected graph
+async fn correct_graph(
+    state: &AppState,
+    auth_user: &AuthUser,
+    id: Uuid,
+    change: impl FnOnce(&DocumentGraph) -> Option<GraphChange>,
+) -> Result<Json<DocumentGraph>, StatusCode> {
+    ensure_document_access(state, auth_user, id).await?;
+    let graph = load_graph(state, id, None).await?;
+    let change = change(&graph).ok_or(StatusCode::NOT_FOUND)?;
+
+    state
+        .db
+        .create_graph_correction(id, &change, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to save graph correction of document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    info!("User {} corrected the knowledge graph of document {} ({})", auth_user.user.id, id, change.kind);
+
+    Ok(Json(load_graph(state, id, None).await?))
+}
+
+/// Rename an entity or change its type. The correction is kept across re-analysis.
+#[utoipa::path(
+    patch,
+    path = "/api/llm/{id}/graph/nodes/{node_id}",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("node_id" = uuid::Uuid, Path, description = "Node ID in the latest graph")
+    ),
+    request_body = RenameGraphNodeRequest,
+    responses(
+        (status = 200, description = "Corrected graph", body = DocumentGraph),
+        (status = 400, description = "Invalid name or label"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or node not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn rename_graph_node(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, node_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<RenameGraphNodeRequest>,
+) -> Result<Json<DocumentGraph>, StatusCode> {
+    if let Err(e) = request.validate() {
+        debug!("Rejected graph node rename: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
-    let nodes_rows = sqlx::query(
-        "SELECT id, label, name, properties FROM document_nodes WHERE document_id = $1"
+    correct_graph(&state, &auth_user, id, |graph| {
+        let node = graph.nodes.iter().find(|node| node.id == node_id)?;
+        let label = request.label.as_deref().unwrap_or(&node.label).trim();
+        Some(GraphChange::rename_node(node, label, request.name.trim()))
+    })
+    .await
+}
+
+/// Remove a wrongly extracted entity with its relationships. The correction is kept
+/// across re-analysis.
+#[utoipa::path(
+    delete,
+    path = "/api/llm/{id}/graph/nodes/{node_id}",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("node_id" = uuid::Uuid, Path, description = "Node ID in the latest graph")
+    ),
+    responses(
+        (status = 200, description = "Corrected graph", body = DocumentGraph),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or node not found"),
+        (status = 500, description = "Internal server error")
     )
-    .bind(id)
-    .fetch_all(pool)
+)]
+async fn delete_graph_node(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, node_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<DocumentGraph>, StatusCode> {
+    correct_graph(&state, &auth_user, id, |graph| {
+        graph.nodes.iter().find(|node| node.id == node_id).map(GraphChange::delete_node)
+    })
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let nodes: Vec<serde_json::Value> = nodes_rows.iter().map(|row| {
-        json!({
-            "id": row.get::<Uuid, _>("id"),
-            "label": row.get::<String, _>("label"),
-            "name": row.get::<String, _>("name"),
-            "properties": row.get::<serde_json::Value, _>("properties")
-        })
-    }).collect();
-
-    let edges_rows = sqlx::query(
-        "SELECT id, source_node_id, target_node_id, relationship, properties FROM document_edges WHERE document_id = $1"
+}
+
+/// Remove a wrong relationship. The correction is kept across re-analysis.
+#[utoipa::path(
+    delete,
+    path = "/api/llm/{id}/graph/edges/{edge_id}",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("edge_id" = uuid::Uuid, Path, description = "Edge ID in the latest graph")
+    ),
+    responses(
+        (status = 200, description = "Corrected graph", body = DocumentGraph),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or edge not found"),
+        (status = 500, description = "Internal server error")
     )
-    .bind(id)
-    .fetch_all(pool)
+)]
+async fn delete_graph_edge(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, edge_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<DocumentGraph>, StatusCode> {
+    correct_graph(&state, &auth_user, id, |graph| {
+        let edge = graph.edges.iter().find(|edge| edge.id == edge_id)?;
+        let source = graph.nodes.iter().find(|node| node.id == edge.source)?;
+        let target = graph.nodes.iter().find(|node| node.id == edge.target)?;
+        Some(GraphChange::delete_edge(source, target, &edge.relationship))
+    })
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let edges: Vec<serde_json::Value> = edges_rows.iter().map(|row| {
-        json!({
-            "id": row.get::<Uuid, _>("id"),
-            "source": row.get::<Uuid, _>("source_node_id"),
-            "target": row.get::<Uuid, _>("target_node_id"),
-            "relationship": row.get::<String, _>("relationship"),
-            "properties": row.get::<serde_json::Value, _>("properties")
-        })
-    }).collect();
-
-    Ok(Json(json!({
-        "nodes": nodes,
-        "edges": edges
-    })))
+}
+
+/// The document's graph corrections, in the order they are applied
+#[utoipa::path(
+    get,
+    path = "/api/llm/{id}/graph/corrections",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Corrections, oldest first", body = Vec<GraphCorrection>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_graph_corrections(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<GraphCorrection>>, StatusCode> {
+    ensure_document_access(&state, &auth_user, id).await?;
+    let corrections = state.db.list_graph_corrections(id).await.map_err(|e| {
+        error!("Failed to list graph corrections of document {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(corrections))
+}
+
+/// Undo a graph correction
+#[utoipa::path(
+    delete,
+    path = "/api/llm/{id}/graph/corrections/{correction_id}",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID"),
+        ("correction_id" = uuid::Uuid, Path, description = "Correction ID")
+    ),
+    responses(
+        (status = 200, description = "Graph without the correction", body = DocumentGraph),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or correction not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn delete_graph_correction(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, correction_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<DocumentGraph>, StatusCode> {
+    ensure_document_access(&state, &auth_user, id).await?;
+    let deleted = state.db.delete_graph_correction(id, correction_id).await.map_err(|e| {
+        error!("Failed to delete graph correction {}: {}", correction_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(load_graph(&state, id, None).await?))
 }
@@ -0,0 +1,94 @@
+use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+
+use crate::{
+    auth::AuthUser,
+    models::{OperationControls, UpdateMaintenanceRequest},
+    routes::queue::require_admin,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::maintenance_service,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/", get(get_maintenance).put(update_maintenance))
+}
+
+/// Whether the instance is in maintenance mode and which queues are paused
+#[utoipa::path(
+    get,
+    path = "/api/maintenance",
+    tag = "maintenance",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Maintenance mode and paused queues", body = OperationControls),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub async fn get_maintenance(_auth_user: AuthUser) -> Json<OperationControls> {
+    Json(maintenance_service::current())
+}
+
+/// Turn the maintenance mode on or off (admin only). While it is on, every node rejects
+/// writes with 503 and the message, reads go on, and the OCR, sync and analysis queues
+/// take no new work.
+#[utoipa::path(
+    put,
+    path = "/api/maintenance",
+    tag = "maintenance",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = UpdateMaintenanceRequest,
+    responses(
+        (status = 200, description = "Maintenance mode changed", body = OperationControls),
+        (status = 400, description = "Message too long"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_maintenance(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<UpdateMaintenanceRequest>,
+) -> Result<Json<OperationControls>, StatusCode> {
+    require_admin(&auth_user)?;
+    let message = request.message().map_err(|reason| {
+        debug!("Rejected maintenance mode change: {}", reason);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let controls = state
+        .db
+        .set_maintenance_mode(request.enabled, message.as_deref(), auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to change the maintenance mode: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    maintenance_service::set(controls.clone());
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::MAINTENANCE_MODE_UPDATE, "instance", None).details(json!({
+            "enabled": controls.maintenance_mode,
+            "message": controls.maintenance_message,
+        })),
+    )
+    .await;
+    info!(
+        "User {} turned the maintenance mode {}",
+        auth_user.user.username,
+        if controls.maintenance_mode { "on" } else { "off" }
+    );
+
+    Ok(Json(controls))
+}
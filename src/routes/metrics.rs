@@ -1,5 +1,5 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
     http::StatusCode,
     response::Json,
     routing::get,
@@ -9,7 +9,13 @@ use std::sync::Arc;
 use serde::Serialize;
 use utoipa::ToSchema;
 
-use crate::{auth::AuthUser, AppState, models::UserRole};
+use crate::{
+    auth::AuthUser,
+    models::{ProcessingCostQuery, ProcessingCostReport, UserRole},
+    monitoring::slow_operations::{self, SlowOperationsQuery, SlowOperationsReport},
+    services::processing_usage,
+    AppState,
+};
 
 fn require_admin(auth_user: &AuthUser) -> Result<(), StatusCode> {
     if auth_user.user.role != UserRole::Admin {
@@ -74,6 +80,98 @@ pub struct GeneralSystemMetrics {
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(get_system_metrics))
+        .route("/prometheus", get(super::prometheus_metrics::get_prometheus_metrics))
+        .route("/slow-operations", get(get_slow_operations).delete(clear_slow_operations))
+        .route("/processing-costs", get(get_processing_costs))
+}
+
+/// Slowest recent search queries, OCR jobs, LLM calls and source syncs
+#[utoipa::path(
+    get,
+    path = "/api/metrics/slow-operations",
+    tag = "metrics",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(SlowOperationsQuery),
+    responses(
+        (status = 200, description = "Timings and the slowest recent operations per category", body = SlowOperationsReport),
+        (status = 401, description = "Unauthorized - valid authentication required"),
+        (status = 403, description = "Forbidden - Admin access required")
+    )
+)]
+pub async fn get_slow_operations(
+    auth_user: AuthUser,
+    Query(query): Query<SlowOperationsQuery>,
+) -> Result<Json<SlowOperationsReport>, StatusCode> {
+    require_admin(&auth_user)?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    Ok(Json(slow_operations::report(query.category, limit)))
+}
+
+/// Forget recorded operations, e.g. after fixing a performance problem
+#[utoipa::path(
+    delete,
+    path = "/api/metrics/slow-operations",
+    tag = "metrics",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Recorded operations cleared"),
+        (status = 401, description = "Unauthorized - valid authentication required"),
+        (status = 403, description = "Forbidden - Admin access required")
+    )
+)]
+pub async fn clear_slow_operations(auth_user: AuthUser) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+    slow_operations::clear();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// OCR time and pages, LLM tokens and storage of the documents processed recently, to
+/// find documents that are unusually expensive and estimate cloud OCR and LLM spend
+#[utoipa::path(
+    get,
+    path = "/api/metrics/processing-costs",
+    tag = "metrics",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(ProcessingCostQuery),
+    responses(
+        (status = 200, description = "Totals of the period, with the users and documents whose processing took the most", body = ProcessingCostReport),
+        (status = 401, description = "Unauthorized - valid authentication required"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_processing_costs(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<ProcessingCostQuery>,
+) -> Result<Json<ProcessingCostReport>, StatusCode> {
+    require_admin(&auth_user)?;
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let sort_by = query.sort_by.unwrap_or_default();
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let (totals, users, documents) = state
+        .db
+        .get_processing_cost_report(days, sort_by, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build processing cost report: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(ProcessingCostReport {
+        days,
+        sort_by,
+        totals: totals.with_ocr_price(processing_usage::ocr_cost_per_1k_pages()),
+        users,
+        documents,
+    }))
 }
 
 #[utoipa::path(
@@ -1,18 +1,74 @@
+pub mod analysis;
+pub mod api_tokens;
+pub mod audit;
 pub mod auth;
+pub mod backups;
+pub mod calendar;
+pub mod client_sync;
+pub mod cluster;
+pub mod collections;
+pub mod consistency;
+pub mod correspondents;
+pub mod document_types;
 pub mod documents;
 pub mod documents_ocr_retry;
+pub mod embargoes;
+pub mod encryption;
+pub mod entities;
+pub mod events;
+pub mod expirations;
+pub mod export;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod groups;
+pub mod guest_portals;
+pub mod health;
+pub mod i18n;
 pub mod ignored_files;
+pub mod import;
+pub mod ingestion_hooks;
+pub mod instance;
+pub mod invoices;
 pub mod labels;
+pub mod legal_holds;
+pub mod library_dav;
 pub mod llm;
+pub mod maintenance;
 pub mod metrics;
 pub mod notifications;
 pub mod ocr;
+pub mod ocr_comparisons;
+pub mod omr;
+pub mod outbound_limits;
+pub mod data_residency;
+pub mod passkeys;
+pub mod physical_locations;
 pub mod prometheus_metrics;
+pub mod quarantine;
 pub mod queue;
+pub mod reminders;
+pub mod retention;
+pub mod saved_searches;
+pub mod scan_bridges;
+pub mod scan_devices;
 pub mod search;
+pub mod sessions;
 pub mod settings;
+pub mod share_links;
+pub mod shares;
 pub mod source_errors;
 pub mod sources;
+pub mod statistics;
+pub mod storage_compression;
+pub mod storage_layout;
+pub mod storage_migrations;
+pub mod storage_quotas;
+pub mod timeline;
+pub mod two_factor;
+pub mod uploads;
 pub mod users;
 pub mod webdav;
-pub mod webdav_scan_failures;
\ No newline at end of file
+pub mod webdav_scan_failures;
+pub mod webhooks;
+pub mod workflow;
+pub mod workspaces;
\ No newline at end of file
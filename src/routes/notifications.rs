@@ -1,23 +1,32 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    extract::{OriginalUri, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     routing::{get, post, delete},
     Json, Router,
 };
 use serde::Deserialize;
 use std::sync::Arc;
+use tracing::{error, warn};
 use utoipa::ToSchema;
+use uuid::Uuid;
 
 use crate::{
     auth::AuthUser,
-    models::{Notification, NotificationSummary},
+    models::{
+        CreateNotificationChannelRequest, DigestPreview, DigestSettings, Notification, NotificationChannel,
+        NotificationSummary, UpdateDigestSettingsRequest, UpdateNotificationChannelRequest,
+    },
+    services::{digest_service::DigestService, notification_service::NotificationService},
+    utils::pagination::{Cursor, Page, PageRequest},
     AppState,
 };
 
 #[derive(Deserialize, ToSchema)]
 struct PaginationQuery {
     limit: Option<i64>,
+    cursor: Option<String>,
     offset: Option<i64>,
+    include_total: Option<bool>,
 }
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -27,6 +36,17 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/{id}/read", post(mark_notification_read))
         .route("/read-all", post(mark_all_notifications_read))
         .route("/{id}", delete(delete_notification))
+        .route("/channels", get(list_notification_channels).post(create_notification_channel))
+        .route(
+            "/channels/{id}",
+            get(get_notification_channel)
+                .put(update_notification_channel)
+                .delete(delete_notification_channel),
+        )
+        .route("/channels/{id}/test", post(test_notification_channel))
+        .route("/digest", get(get_digest_settings).put(update_digest_settings))
+        .route("/digest/preview", get(preview_digest))
+        .route("/digest/send", post(send_digest))
 }
 
 #[utoipa::path(
@@ -37,11 +57,14 @@ pub fn router() -> Router<Arc<AppState>> {
         ("bearer_auth" = [])
     ),
     params(
-        ("limit" = Option<i64>, Query, description = "Number of notifications to return (default: 25)"),
-        ("offset" = Option<i64>, Query, description = "Number of notifications to skip (default: 0)")
+        ("limit" = Option<i64>, Query, description = "Number of notifications to return (default: 25, max: 100)"),
+        ("cursor" = Option<String>, Query, description = "Cursor of the next page, from the `Link` header of the previous one"),
+        ("offset" = Option<i64>, Query, description = "Deprecated: use `cursor`. Number of notifications to skip"),
+        ("include_total" = Option<bool>, Query, description = "Send `X-Total-Count` (default: true)")
     ),
     responses(
-        (status = 200, description = "List of user notifications", body = Vec<Notification>),
+        (status = 200, description = "User notifications, newest first, with a `Link` to the next page and `X-Total-Count`", body = Vec<Notification>),
+        (status = 400, description = "Invalid cursor, or a cursor combined with an offset"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     )
@@ -49,18 +72,40 @@ pub fn router() -> Router<Arc<AppState>> {
 async fn get_notifications(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    OriginalUri(uri): OriginalUri,
     Query(pagination): Query<PaginationQuery>,
-) -> Result<Json<Vec<Notification>>, StatusCode> {
-    let limit = pagination.limit.unwrap_or(25);
-    let offset = pagination.offset.unwrap_or(0);
-    
+) -> Result<(HeaderMap, Json<Vec<Notification>>), StatusCode> {
+    let page = PageRequest::parse(
+        pagination.limit,
+        25,
+        100,
+        pagination.cursor.as_deref(),
+        pagination.offset,
+        pagination.include_total,
+    )
+    .map_err(|_| StatusCode::BAD_REQUEST)?;
+
     let notifications = state
         .db
-        .get_user_notifications(auth_user.user.id, limit, offset)
+        .get_user_notifications_page(auth_user.user.id, &page)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    Ok(Json(notifications))
+    let total = if page.include_total {
+        Some(
+            state
+                .db
+                .count_user_notifications(auth_user.user.id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        )
+    } else {
+        None
+    };
+
+    let page = Page::new(notifications, page.limit, total, |notification| {
+        Cursor::new(notification.created_at, notification.id)
+    });
+    Ok((page.headers(&uri), Json(page.items)))
 }
 
 #[utoipa::path(
@@ -175,4 +220,364 @@ async fn delete_notification(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
     Ok(StatusCode::OK)
-}
\ No newline at end of file
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/notifications/channels",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Outbound notification channels of the user", body = Vec<NotificationChannel>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_notification_channels(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<NotificationChannel>>, StatusCode> {
+    let channels = state
+        .db
+        .get_notification_channels(auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to list notification channels: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(channels))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/notifications/channels",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateNotificationChannelRequest,
+    responses(
+        (status = 201, description = "Notification channel created", body = NotificationChannel),
+        (status = 400, description = "Invalid name, configuration or notification types"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_notification_channel(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateNotificationChannelRequest>,
+) -> Result<(StatusCode, Json<NotificationChannel>), StatusCode> {
+    if let Err(reason) = request.validate() {
+        warn!("Rejected notification channel: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let channel = state
+        .db
+        .create_notification_channel(auth_user.user.id, &request)
+        .await
+        .map_err(|e| {
+            error!("Failed to create notification channel: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok((StatusCode::CREATED, Json(channel)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/notifications/channels/{id}",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Notification channel ID")
+    ),
+    responses(
+        (status = 200, description = "Notification channel", body = NotificationChannel),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Notification channel not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_notification_channel(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<NotificationChannel>, StatusCode> {
+    let channel = state
+        .db
+        .get_notification_channel(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get notification channel {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(channel))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/notifications/channels/{id}",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Notification channel ID")
+    ),
+    request_body = UpdateNotificationChannelRequest,
+    responses(
+        (status = 200, description = "Notification channel updated", body = NotificationChannel),
+        (status = 400, description = "Invalid name, configuration or notification types"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Notification channel not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_notification_channel(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateNotificationChannelRequest>,
+) -> Result<Json<NotificationChannel>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        warn!("Rejected notification channel update: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let channel = state
+        .db
+        .update_notification_channel(id, auth_user.user.id, &request)
+        .await
+        .map_err(|e| {
+            error!("Failed to update notification channel {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(channel))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/notifications/channels/{id}",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Notification channel ID")
+    ),
+    responses(
+        (status = 204, description = "Notification channel deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Notification channel not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_notification_channel(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_notification_channel(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete notification channel {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Send a test notification to a channel. A failure is recorded in the channel's `last_error`.
+#[utoipa::path(
+    post,
+    path = "/api/notifications/channels/{id}/test",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Notification channel ID")
+    ),
+    responses(
+        (status = 204, description = "Test notification sent"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Notification channel not found"),
+        (status = 502, description = "The channel did not accept the notification"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn test_notification_channel(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let channel = state
+        .db
+        .get_notification_channel(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get notification channel {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    NotificationService::new(state.db.clone())
+        .send_test(&channel)
+        .await
+        .map_err(|e| {
+            warn!("Test notification to channel {} failed: {}", id, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn digest_settings(state: &AppState, user_id: Uuid) -> Result<DigestSettings, StatusCode> {
+    let settings = state.db.get_digest_settings(user_id).await.map_err(|e| {
+        error!("Failed to get digest settings of user {}: {}", user_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(settings.unwrap_or_else(|| DigestSettings::default_for(user_id, chrono::Utc::now())))
+}
+
+/// The current user's email digest settings; digests are off until they are saved
+#[utoipa::path(
+    get,
+    path = "/api/notifications/digest",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Digest settings", body = DigestSettings),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_digest_settings(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<DigestSettings>, StatusCode> {
+    Ok(Json(digest_settings(&state, auth_user.user.id).await?))
+}
+
+/// Change how often the current user gets an email digest. Digests are sent at
+/// `send_hour` (UTC), weekly ones on `weekday`; fields left out keep their value.
+#[utoipa::path(
+    put,
+    path = "/api/notifications/digest",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = UpdateDigestSettingsRequest,
+    responses(
+        (status = 200, description = "Digest settings saved", body = DigestSettings),
+        (status = 400, description = "Hour or weekday out of range"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_digest_settings(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateDigestSettingsRequest>,
+) -> Result<Json<DigestSettings>, StatusCode> {
+    if let Err(e) = request.validate() {
+        warn!("Invalid digest settings: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let settings = state.db.save_digest_settings(auth_user.user.id, &request).await.map_err(|e| {
+        error!("Failed to save digest settings of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(settings))
+}
+
+/// The current user's next digest as it would be emailed now
+#[utoipa::path(
+    get,
+    path = "/api/notifications/digest/preview",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Rendered digest and what it reports", body = DigestPreview),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn preview_digest(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<DigestPreview>, StatusCode> {
+    let settings = digest_settings(&state, auth_user.user.id).await?;
+    let preview = DigestService::new(state.db.clone())
+        .preview(&settings, chrono::Utc::now())
+        .await
+        .map_err(|e| {
+            error!("Failed to build digest of user {}: {}", auth_user.user.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(preview))
+}
+
+/// Email the current user's next digest now, as a test. The scheduled digest still
+/// goes out and covers the same period.
+#[utoipa::path(
+    post,
+    path = "/api/notifications/digest/send",
+    tag = "notifications",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Digest sent"),
+        (status = 401, description = "Unauthorized"),
+        (status = 502, description = "No email channel accepted the digest"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn send_digest(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, StatusCode> {
+    let settings = digest_settings(&state, auth_user.user.id).await?;
+    let service = DigestService::new(state.db.clone());
+    let preview = service.preview(&settings, chrono::Utc::now()).await.map_err(|e| {
+        error!("Failed to build digest of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    service
+        .deliver(auth_user.user.id, &preview.subject, &preview.body)
+        .await
+        .map_err(|e| {
+            warn!("Test digest of user {} was not sent: {}", auth_user.user.id, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,262 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        CreateOcrComparisonRequest, OcrComparison, OcrComparisonDocument, OcrComparisonReport, OcrEngineConfig,
+    },
+    routes::queue::require_admin,
+    services::llm::llm_service::LLMService,
+    services::ocr_comparison_service::OcrComparisonService,
+    AppState,
+};
+
+/// Comparisons listed at most
+const OCR_COMPARISON_LIST_LIMIT: i64 = 100;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_ocr_comparisons).post(create_ocr_comparison))
+        .route("/{id}", get(get_ocr_comparison).delete(delete_ocr_comparison))
+        .route("/{id}/documents", get(get_ocr_comparison_documents))
+}
+
+async fn existing_comparison(state: &AppState, id: Uuid) -> Result<OcrComparison, StatusCode> {
+    state
+        .db
+        .get_ocr_comparison(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get OCR comparison {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Compare two OCR engines on a sample of documents
+///
+/// Both engines read every document of the sample in the background. Documents with
+/// a reference text, given in `ground_truth` or corrected by hand, are scored by
+/// character and word accuracy; the others only by how much the engines agree.
+#[utoipa::path(
+    post,
+    path = "/api/ocr/comparisons",
+    tag = "ocr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateOcrComparisonRequest,
+    responses(
+        (status = 202, description = "Comparison queued; follow it at /api/ocr/comparisons/{id}", body = OcrComparison),
+        (status = 400, description = "Invalid engines, documents or filters, no matching documents, or a vision engine without a vision model"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_ocr_comparison(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateOcrComparisonRequest>,
+) -> Result<(StatusCode, Json<OcrComparison>), StatusCode> {
+    require_admin(&auth_user)?;
+    request.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let uses_vision = [&request.engine_a, &request.engine_b].contains(&&OcrEngineConfig::Vision);
+    if uses_vision && LLMService::new(state.db.get_pool().clone()).vision_model().is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let document_ids = match &request.document_ids {
+        Some(ids) => ids.clone(),
+        None => state
+            .db
+            .find_ocr_comparison_sample(
+                request.label_id,
+                request.document_type_id,
+                request.mime_type.as_deref(),
+                request.sample_size(),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to sample documents for an OCR comparison: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?,
+    };
+
+    let comparison = state
+        .db
+        .create_ocr_comparison(
+            auth_user.user.id,
+            &request.name,
+            &request.engine_a,
+            &request.engine_b,
+            &document_ids,
+            &request.ground_truth,
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create OCR comparison: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if comparison.total_documents == 0 {
+        let _ = state.db.delete_ocr_comparison(comparison.id).await;
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    info!(
+        "User {} started OCR comparison {} on {} documents",
+        auth_user.user.id, comparison.id, comparison.total_documents
+    );
+    OcrComparisonService::new(state.db.clone(), (*state.file_service).clone()).spawn(comparison.id);
+
+    Ok((StatusCode::ACCEPTED, Json(comparison)))
+}
+
+/// OCR comparisons, newest first
+#[utoipa::path(
+    get,
+    path = "/api/ocr/comparisons",
+    tag = "ocr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Recent OCR comparisons with their progress", body = Vec<OcrComparison>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_ocr_comparisons(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<OcrComparison>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let comparisons = state.db.list_ocr_comparisons(OCR_COMPARISON_LIST_LIMIT).await.map_err(|e| {
+        error!("Failed to list OCR comparisons: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(comparisons))
+}
+
+/// An OCR comparison with the engines' results per document class
+///
+/// Results cover the documents processed so far, so the report fills in while the
+/// comparison runs.
+#[utoipa::path(
+    get,
+    path = "/api/ocr/comparisons/{id}",
+    tag = "ocr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "OCR comparison ID")
+    ),
+    responses(
+        (status = 200, description = "Comparison report", body = OcrComparisonReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Comparison not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_ocr_comparison(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OcrComparisonReport>, StatusCode> {
+    require_admin(&auth_user)?;
+    let comparison = existing_comparison(&state, id).await?;
+
+    let documents = state.db.get_ocr_comparison_documents(id).await.map_err(|e| {
+        error!("Failed to get documents of OCR comparison {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(OcrComparisonReport::new(comparison, &documents)))
+}
+
+/// Both engines' text, scores and timing for every document of a comparison
+#[utoipa::path(
+    get,
+    path = "/api/ocr/comparisons/{id}/documents",
+    tag = "ocr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "OCR comparison ID")
+    ),
+    responses(
+        (status = 200, description = "Documents of the comparison in sample order", body = Vec<OcrComparisonDocument>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Comparison not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_ocr_comparison_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<OcrComparisonDocument>>, StatusCode> {
+    require_admin(&auth_user)?;
+    existing_comparison(&state, id).await?;
+
+    let documents = state.db.get_ocr_comparison_documents(id).await.map_err(|e| {
+        error!("Failed to get documents of OCR comparison {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(documents))
+}
+
+/// Delete an OCR comparison and its results, stopping it if it is still running
+#[utoipa::path(
+    delete,
+    path = "/api/ocr/comparisons/{id}",
+    tag = "ocr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "OCR comparison ID")
+    ),
+    responses(
+        (status = 204, description = "Comparison deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Comparison not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_ocr_comparison(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let deleted = state.db.delete_ocr_comparison(id).await.map_err(|e| {
+        error!("Failed to delete OCR comparison {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("User {} deleted OCR comparison {}", auth_user.user.id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
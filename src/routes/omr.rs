@@ -0,0 +1,307 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        CreateOmrTemplateRequest, OmrFieldCount, OmrResultListItem, OmrResultsQuery, OmrTemplate,
+        UpdateOmrTemplateRequest,
+    },
+    routes::queue::require_admin,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/templates", get(list_omr_templates).post(create_omr_template))
+        .route(
+            "/templates/{id}",
+            get(get_omr_template).put(update_omr_template).delete(delete_omr_template),
+        )
+        .route("/templates/{id}/results", get(list_omr_results))
+        .route("/templates/{id}/summary", get(get_omr_summary))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+fn map_write_error(e: anyhow::Error) -> StatusCode {
+    if e.to_string().contains("duplicate key") {
+        debug!("Rejected form template with a name already in use");
+        StatusCode::CONFLICT
+    } else {
+        internal_error("Failed to save form template", e)
+    }
+}
+
+async fn load_template(state: &AppState, id: Uuid) -> Result<OmrTemplate, StatusCode> {
+    state
+        .db
+        .get_omr_template(id)
+        .await
+        .map_err(|e| internal_error("Failed to get form template", e))?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Form templates scanned documents are read with
+#[utoipa::path(
+    get,
+    path = "/api/omr/templates",
+    tag = "omr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Form templates", body = Vec<OmrTemplate>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_omr_templates(
+    State(state): State<Arc<AppState>>,
+    _auth_user: AuthUser,
+) -> Result<Json<Vec<OmrTemplate>>, StatusCode> {
+    let templates = state
+        .db
+        .get_omr_templates()
+        .await
+        .map_err(|e| internal_error("Failed to list form templates", e))?;
+
+    Ok(Json(templates))
+}
+
+/// Define a form template (admin only)
+///
+/// Regions are the checkboxes and fields of one page of the blank form, as fractions
+/// of the page's width and height. Scanned documents containing all match phrases are
+/// read with the template after OCR.
+#[utoipa::path(
+    post,
+    path = "/api/omr/templates",
+    tag = "omr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateOmrTemplateRequest,
+    responses(
+        (status = 201, description = "Form template created", body = OmrTemplate),
+        (status = 400, description = "Invalid name, settings or regions"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 409, description = "A template with this name exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_omr_template(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateOmrTemplateRequest>,
+) -> Result<(StatusCode, Json<OmrTemplate>), StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected form template: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let template = state
+        .db
+        .create_omr_template(auth_user.user.id, &request)
+        .await
+        .map_err(map_write_error)?;
+    info!("User {} created form template '{}'", auth_user.user.username, template.name);
+
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/omr/templates/{id}",
+    tag = "omr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Template ID")
+    ),
+    responses(
+        (status = 200, description = "Form template", body = OmrTemplate),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Template not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_omr_template(
+    State(state): State<Arc<AppState>>,
+    _auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OmrTemplate>, StatusCode> {
+    Ok(Json(load_template(&state, id).await?))
+}
+
+/// Change a form template (admin only). Results read before keep their data until
+/// their documents are read again.
+#[utoipa::path(
+    put,
+    path = "/api/omr/templates/{id}",
+    tag = "omr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Template ID")
+    ),
+    request_body = UpdateOmrTemplateRequest,
+    responses(
+        (status = 200, description = "Form template updated", body = OmrTemplate),
+        (status = 400, description = "Invalid name, settings or regions"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Template not found"),
+        (status = 409, description = "A template with this name exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_omr_template(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateOmrTemplateRequest>,
+) -> Result<Json<OmrTemplate>, StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected form template update: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let template = state
+        .db
+        .update_omr_template(id, &request)
+        .await
+        .map_err(map_write_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(template))
+}
+
+/// Delete a form template and the data read with it (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/omr/templates/{id}",
+    tag = "omr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Template ID")
+    ),
+    responses(
+        (status = 204, description = "Form template deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Template not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_omr_template(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+    let deleted = state
+        .db
+        .delete_omr_template(id)
+        .await
+        .map_err(|e| internal_error("Failed to delete form template", e))?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    info!("User {} deleted form template {}", auth_user.user.username, id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Data read with a template from the documents the user can see, newest first
+///
+/// `field` and `value` narrow the list to forms where a field has a value, e.g.
+/// `field=consent&value=true` or `field=color&value=red`.
+#[utoipa::path(
+    get,
+    path = "/api/omr/templates/{id}/results",
+    tag = "omr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Template ID"),
+        OmrResultsQuery
+    ),
+    responses(
+        (status = 200, description = "Results of the template", body = Vec<OmrResultListItem>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Template not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_omr_results(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(query): Query<OmrResultsQuery>,
+) -> Result<Json<Vec<OmrResultListItem>>, StatusCode> {
+    load_template(&state, id).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let results = state
+        .db
+        .get_omr_results(id, auth_user.user.id, auth_user.user.role, query.filter(), limit, offset)
+        .await
+        .map_err(|e| internal_error("Failed to list form results", e))?;
+
+    Ok(Json(results))
+}
+
+/// How often each checkbox and option of a template was marked, over the documents
+/// the user can see. Free text fields are left out.
+#[utoipa::path(
+    get,
+    path = "/api/omr/templates/{id}/summary",
+    tag = "omr",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Template ID")
+    ),
+    responses(
+        (status = 200, description = "Count of each value per field", body = Vec<OmrFieldCount>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Template not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_omr_summary(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<OmrFieldCount>>, StatusCode> {
+    let template = load_template(&state, id).await?;
+    let counts = state
+        .db
+        .get_omr_summary(id, auth_user.user.id, auth_user.user.role, &template.text_keys())
+        .await
+        .map_err(|e| internal_error("Failed to summarize form results", e))?;
+
+    Ok(Json(counts))
+}
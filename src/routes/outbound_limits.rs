@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+use std::sync::Arc;
+use tracing::{debug, error, info};
+
+use crate::{
+    auth::AuthUser,
+    models::{OutboundLimit, OutboundLimitStatus, OutboundScope},
+    routes::queue::require_admin,
+    services::outbound_limits,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_outbound_limits))
+        .route("/{scope}", put(set_outbound_limit).delete(reset_outbound_limit))
+}
+
+fn status_of(scope: OutboundScope) -> Result<OutboundLimitStatus, StatusCode> {
+    outbound_limits::status()
+        .into_iter()
+        .find(|status| status.scope == scope)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Concurrency and rate limits on requests to the LLM and sync servers, with their
+/// current load (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/outbound-limits",
+    tag = "outbound_limits",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Limits in force per scope", body = Vec<OutboundLimitStatus>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required")
+    )
+)]
+pub async fn list_outbound_limits(auth_user: AuthUser) -> Result<Json<Vec<OutboundLimitStatus>>, StatusCode> {
+    require_admin(&auth_user)?;
+    Ok(Json(outbound_limits::status()))
+}
+
+/// Replace the limits of a scope (admin only)
+///
+/// Limits left out do not apply. Requests already waiting or in flight finish under
+/// the previous limits; other instances follow within a minute.
+#[utoipa::path(
+    put,
+    path = "/api/outbound-limits/{scope}",
+    tag = "outbound_limits",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("scope" = OutboundScope, Path, description = "global, llm, webdav, onedrive or dropbox")
+    ),
+    request_body = OutboundLimit,
+    responses(
+        (status = 200, description = "Limits in force", body = OutboundLimitStatus),
+        (status = 400, description = "Invalid limits"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn set_outbound_limit(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(scope): Path<OutboundScope>,
+    Json(limit): Json<OutboundLimit>,
+) -> Result<Json<OutboundLimitStatus>, StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = limit.validate() {
+        debug!("Rejected outbound limits for {}: {}", scope, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    outbound_limits::set(&state.db, scope, limit, auth_user.user.id).await.map_err(|e| {
+        error!("Failed to save outbound limits for {}: {}", scope, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("User {} set outbound limits for {}: {:?}", auth_user.user.username, scope, limit);
+
+    Ok(Json(status_of(scope)?))
+}
+
+/// Go back to the limits from the environment (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/outbound-limits/{scope}",
+    tag = "outbound_limits",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("scope" = OutboundScope, Path, description = "global, llm, webdav, onedrive or dropbox")
+    ),
+    responses(
+        (status = 200, description = "Limits in force", body = OutboundLimitStatus),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn reset_outbound_limit(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(scope): Path<OutboundScope>,
+) -> Result<Json<OutboundLimitStatus>, StatusCode> {
+    require_admin(&auth_user)?;
+    outbound_limits::reset(&state.db, scope).await.map_err(|e| {
+        error!("Failed to reset outbound limits for {}: {}", scope, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("User {} reset outbound limits for {}", auth_user.user.username, scope);
+
+    Ok(Json(status_of(scope)?))
+}
@@ -0,0 +1,474 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    Passkey, PasskeyAuthentication, PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential,
+};
+use webauthn_rs::Webauthn;
+
+use crate::{
+    auth::{start_session, AuthUser},
+    models::{
+        FinishPasskeyLoginRequest, FinishPasskeyRegistrationRequest, LoginResponse, PasskeyChallengeResponse,
+        RenamePasskeyRequest, StartPasskeyLoginRequest, UserPasskey,
+    },
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        passkey_service::{self, CEREMONY_AUTHENTICATION, CEREMONY_REGISTRATION},
+    },
+    AppState,
+};
+
+/// The relying party, or 400 when passkeys are not configured
+fn webauthn() -> Result<&'static Webauthn, StatusCode> {
+    passkey_service::webauthn().ok_or(StatusCode::BAD_REQUEST)
+}
+
+fn internal_error(context: &str, e: impl std::fmt::Display) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// The stored credentials of a user's passkeys
+fn stored_credentials(passkeys: &[UserPasskey]) -> Result<Vec<Passkey>, StatusCode> {
+    passkeys
+        .iter()
+        .map(|passkey| {
+            serde_json::from_value::<Passkey>(passkey.passkey.clone())
+                .map_err(|e| internal_error("Failed to read stored passkey", e))
+        })
+        .collect()
+}
+
+/// List the current user's passkeys
+#[utoipa::path(
+    get,
+    path = "/api/users/me/passkeys",
+    tag = "passkeys",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Passkeys of the current user, oldest first", body = Vec<UserPasskey>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_passkeys(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<UserPasskey>>, StatusCode> {
+    let passkeys = state
+        .db
+        .get_user_passkeys(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list passkeys", e))?;
+
+    Ok(Json(passkeys))
+}
+
+/// Start registering a passkey for the current user
+///
+/// Pass `options` to `navigator.credentials.create()` and send the result to
+/// `/api/users/me/passkeys/register/finish` within five minutes.
+#[utoipa::path(
+    post,
+    path = "/api/users/me/passkeys/register/start",
+    tag = "passkeys",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Credential creation options", body = PasskeyChallengeResponse),
+        (status = 400, description = "Passkeys are not configured, or not a local account"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "The user already has the maximum number of passkeys"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn start_passkey_registration(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<PasskeyChallengeResponse>, StatusCode> {
+    let webauthn = webauthn()?;
+    let user = &auth_user.user;
+    // OIDC accounts sign in through their identity provider
+    if user.password_hash.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let existing = state
+        .db
+        .get_user_passkeys(user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list passkeys", e))?;
+    if existing.len() >= passkey_service::MAX_PASSKEYS_PER_USER {
+        return Err(StatusCode::CONFLICT);
+    }
+    // The authenticator refuses to register a second passkey for the same account
+    let exclude = stored_credentials(&existing)?
+        .iter()
+        .map(|passkey| passkey.cred_id().clone())
+        .collect();
+
+    let (options, registration) = webauthn
+        .start_passkey_registration(user.id, &user.username, &user.username, Some(exclude))
+        .map_err(|e| internal_error("Failed to start passkey registration", e))?;
+
+    let registration = serde_json::to_value(&registration)
+        .map_err(|e| internal_error("Failed to serialize passkey registration", e))?;
+    let ceremony_id = state
+        .db
+        .create_webauthn_ceremony(
+            user.id,
+            CEREMONY_REGISTRATION,
+            &registration,
+            passkey_service::CEREMONY_TIMEOUT_MINUTES,
+        )
+        .await
+        .map_err(|e| internal_error("Failed to store passkey registration", e))?;
+
+    Ok(Json(PasskeyChallengeResponse {
+        ceremony_id,
+        options: serde_json::to_value(&options)
+            .map_err(|e| internal_error("Failed to serialize credential creation options", e))?,
+    }))
+}
+
+/// Finish registering a passkey with the browser's answer
+#[utoipa::path(
+    post,
+    path = "/api/users/me/passkeys/register/finish",
+    tag = "passkeys",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = FinishPasskeyRegistrationRequest,
+    responses(
+        (status = 201, description = "Passkey registered", body = UserPasskey),
+        (status = 400, description = "Passkeys are not configured, empty or too long name, or the credential was rejected"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Unknown or expired ceremony"),
+        (status = 409, description = "The passkey is already registered"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn finish_passkey_registration(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<FinishPasskeyRegistrationRequest>,
+) -> Result<(StatusCode, Json<UserPasskey>), StatusCode> {
+    let webauthn = webauthn()?;
+    let user = &auth_user.user;
+    let name = passkey_service::normalize_name(&request.name).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let (ceremony_user, registration) = state
+        .db
+        .take_webauthn_ceremony(request.ceremony_id, CEREMONY_REGISTRATION)
+        .await
+        .map_err(|e| internal_error("Failed to load passkey registration", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if ceremony_user != user.id {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let registration: PasskeyRegistration = serde_json::from_value(registration)
+        .map_err(|e| internal_error("Failed to read passkey registration", e))?;
+    let credential: RegisterPublicKeyCredential =
+        serde_json::from_value(request.credential).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let passkey = webauthn
+        .finish_passkey_registration(&credential, &registration)
+        .map_err(|e| {
+            warn!("Rejected passkey registration for user {}: {}", user.id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let credential_id = passkey.cred_id().as_ref().to_vec();
+    if state
+        .db
+        .get_passkey_by_credential_id(&credential_id)
+        .await
+        .map_err(|e| internal_error("Failed to look up passkey", e))?
+        .is_some()
+    {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let stored = serde_json::to_value(&passkey).map_err(|e| internal_error("Failed to serialize passkey", e))?;
+    let passkey = state
+        .db
+        .create_user_passkey(user.id, &name, &credential_id, &stored)
+        .await
+        .map_err(|e| internal_error("Failed to store passkey", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(user),
+        &client,
+        AuditEvent::new(audit_service::PASSKEY_ADD, "user", Some(user.id))
+            .details(json!({ "passkey_id": passkey.id, "name": passkey.name })),
+    )
+    .await;
+    info!("User {} registered passkey '{}'", user.username, passkey.name);
+
+    Ok((StatusCode::CREATED, Json(passkey)))
+}
+
+/// Rename one of the current user's passkeys
+#[utoipa::path(
+    put,
+    path = "/api/users/me/passkeys/{id}",
+    tag = "passkeys",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Passkey ID")
+    ),
+    request_body = RenamePasskeyRequest,
+    responses(
+        (status = 200, description = "Passkey renamed", body = UserPasskey),
+        (status = 400, description = "Empty or too long name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Passkey not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn rename_passkey(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(passkey_id): Path<Uuid>,
+    Json(request): Json<RenamePasskeyRequest>,
+) -> Result<Json<UserPasskey>, StatusCode> {
+    let name = passkey_service::normalize_name(&request.name).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let passkey = state
+        .db
+        .rename_user_passkey(passkey_id, auth_user.user.id, &name)
+        .await
+        .map_err(|e| internal_error("Failed to rename passkey", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(passkey))
+}
+
+/// Remove one of the current user's passkeys
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/passkeys/{id}",
+    tag = "passkeys",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Passkey ID")
+    ),
+    responses(
+        (status = 204, description = "Passkey removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Passkey not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_passkey(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(passkey_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_user_passkey(passkey_id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to delete passkey", e))?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::PASSKEY_REMOVE, "user", Some(auth_user.user.id))
+            .details(json!({ "passkey_id": passkey_id })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Start logging in with a passkey
+///
+/// Pass `options` to `navigator.credentials.get()` and send the result to
+/// `/api/auth/passkey/finish` within five minutes.
+#[utoipa::path(
+    post,
+    path = "/api/auth/passkey/start",
+    tag = "auth",
+    request_body = StartPasskeyLoginRequest,
+    responses(
+        (status = 200, description = "Credential request options", body = PasskeyChallengeResponse),
+        (status = 400, description = "Passkeys are not configured"),
+        (status = 401, description = "Unknown user or no passkeys registered"),
+        (status = 403, description = "Local authentication is disabled"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn start_passkey_login(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<StartPasskeyLoginRequest>,
+) -> Result<Json<PasskeyChallengeResponse>, StatusCode> {
+    if !state.config.allow_local_auth.unwrap_or(true) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let webauthn = webauthn()?;
+
+    let user = state
+        .db
+        .get_user_by_username(&request.username)
+        .await
+        .map_err(|e| internal_error("Failed to load user", e))?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let passkeys = state
+        .db
+        .get_user_passkeys(user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list passkeys", e))?;
+    if passkeys.is_empty() || user.password_hash.is_none() {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let (options, authentication) = webauthn
+        .start_passkey_authentication(&stored_credentials(&passkeys)?)
+        .map_err(|e| internal_error("Failed to start passkey login", e))?;
+
+    let authentication = serde_json::to_value(&authentication)
+        .map_err(|e| internal_error("Failed to serialize passkey login", e))?;
+    let ceremony_id = state
+        .db
+        .create_webauthn_ceremony(
+            user.id,
+            CEREMONY_AUTHENTICATION,
+            &authentication,
+            passkey_service::CEREMONY_TIMEOUT_MINUTES,
+        )
+        .await
+        .map_err(|e| internal_error("Failed to store passkey login", e))?;
+
+    Ok(Json(PasskeyChallengeResponse {
+        ceremony_id,
+        options: serde_json::to_value(&options)
+            .map_err(|e| internal_error("Failed to serialize credential request options", e))?,
+    }))
+}
+
+/// Finish logging in with the browser's answer
+///
+/// A passkey proves both possession and user verification, so it also satisfies
+/// two-factor login.
+#[utoipa::path(
+    post,
+    path = "/api/auth/passkey/finish",
+    tag = "auth",
+    request_body = FinishPasskeyLoginRequest,
+    responses(
+        (status = 200, description = "Login successful", body = LoginResponse),
+        (status = 400, description = "Passkeys are not configured"),
+        (status = 401, description = "Unknown or expired ceremony, or the passkey was rejected"),
+        (status = 403, description = "Local authentication is disabled"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn finish_passkey_login(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(request): Json<FinishPasskeyLoginRequest>,
+) -> Result<Json<LoginResponse>, StatusCode> {
+    if !state.config.allow_local_auth.unwrap_or(true) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let webauthn = webauthn()?;
+
+    let (user_id, authentication) = state
+        .db
+        .take_webauthn_ceremony(request.ceremony_id, CEREMONY_AUTHENTICATION)
+        .await
+        .map_err(|e| internal_error("Failed to load passkey login", e))?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let user = state
+        .db
+        .get_user_by_id(user_id)
+        .await
+        .map_err(|e| internal_error("Failed to load user", e))?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let login_failed = || {
+        AuditEvent::new(audit_service::LOGIN_FAILED, "user", Some(user.id))
+            .details(json!({ "username": user.username, "reason": "invalid_passkey" }))
+    };
+
+    let authentication: PasskeyAuthentication = serde_json::from_value(authentication)
+        .map_err(|e| internal_error("Failed to read passkey login", e))?;
+    let credential: PublicKeyCredential =
+        serde_json::from_value(request.credential).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let result = match webauthn.finish_passkey_authentication(&credential, &authentication) {
+        Ok(result) => result,
+        Err(e) => {
+            warn!("Rejected passkey login for user {}: {}", user.id, e);
+            audit_service::record(&state.db, None, &client, login_failed()).await;
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    };
+
+    // The passkey was offered to this user at start, so it can only be theirs unless it
+    // was removed in the meantime
+    let Some(stored) = state
+        .db
+        .get_passkey_by_credential_id(result.cred_id().as_ref())
+        .await
+        .map_err(|e| internal_error("Failed to look up passkey", e))?
+        .filter(|passkey| passkey.user_id == user.id)
+    else {
+        audit_service::record(&state.db, None, &client, login_failed()).await;
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let mut passkey: Passkey = serde_json::from_value(stored.passkey.clone())
+        .map_err(|e| internal_error("Failed to read stored passkey", e))?;
+    let updated = match passkey.update_credential(&result) {
+        Some(true) => Some(
+            serde_json::to_value(&passkey).map_err(|e| internal_error("Failed to serialize passkey", e))?,
+        ),
+        _ => None,
+    };
+    state
+        .db
+        .touch_user_passkey(stored.id, updated.as_ref())
+        .await
+        .map_err(|e| internal_error("Failed to update passkey", e))?;
+
+    let token = start_session(&state, &user, "passkey", &client)
+        .await
+        .map_err(|e| internal_error("Failed to start session", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(&user),
+        &client,
+        AuditEvent::new(audit_service::LOGIN, "user", Some(user.id))
+            .details(json!({ "method": "passkey", "passkey_id": stored.id })),
+    )
+    .await;
+
+    Ok(Json(LoginResponse {
+        token,
+        user: user.into(),
+    }))
+}
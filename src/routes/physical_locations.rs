@@ -0,0 +1,515 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        location_chain, location_paths, AssignPhysicalLocationRequest, AssignPhysicalLocationResponse,
+        CreatePhysicalLocationRequest, DocumentResponse, PhysicalLocation, PhysicalLocationDocumentsQuery,
+        PhysicalLocationStats, PhysicalLocationStatsResponse, RetentionLocationReport, RetentionReport,
+        UpdatePhysicalLocationRequest, PHYSICAL_LOCATION_KINDS,
+    },
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_physical_locations).post(create_physical_location))
+        .route("/assign", post(assign_physical_location))
+        .route("/stats", get(get_physical_location_stats))
+        .route("/retention-report", get(get_retention_report))
+        .route(
+            "/{id}",
+            get(get_physical_location)
+                .put(update_physical_location)
+                .delete(delete_physical_location),
+        )
+        .route("/{id}/documents", get(list_physical_location_documents))
+}
+
+/// Position of a kind from the outermost; a location may only be inside one of the same or an outer kind
+fn kind_rank(kind: &str) -> Result<usize, StatusCode> {
+    PHYSICAL_LOCATION_KINDS
+        .iter()
+        .position(|k| *k == kind)
+        .ok_or(StatusCode::BAD_REQUEST)
+}
+
+fn map_write_error(e: anyhow::Error) -> StatusCode {
+    error!("Failed to save physical location: {}", e);
+    if e.to_string().contains("duplicate key") {
+        StatusCode::CONFLICT
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+async fn retention_days(state: &AppState, user_id: Uuid) -> Result<Option<i32>, StatusCode> {
+    let settings = state.db.get_user_settings(user_id).await.map_err(|e| {
+        error!("Failed to get settings for user {}: {}", user_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(settings.and_then(|s| s.retention_days).filter(|days| *days > 0))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/physical-locations",
+    tag = "physical_locations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Physical locations of the current user", body = Vec<PhysicalLocation>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_physical_locations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<PhysicalLocation>>, StatusCode> {
+    let locations = state
+        .db
+        .get_physical_locations(auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to list physical locations: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(locations))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/physical-locations",
+    tag = "physical_locations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreatePhysicalLocationRequest,
+    responses(
+        (status = 201, description = "Physical location created", body = PhysicalLocation),
+        (status = 400, description = "Invalid kind, empty name, or a parent of an inner kind"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Parent location not found"),
+        (status = 409, description = "The parent already holds a location with this name"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_physical_location(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreatePhysicalLocationRequest>,
+) -> Result<(StatusCode, Json<PhysicalLocation>), StatusCode> {
+    let rank = kind_rank(&request.kind)?;
+    if request.name.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if let Some(parent_id) = request.parent_id {
+        let parent = state
+            .db
+            .get_physical_location(parent_id, auth_user.user.id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get physical location {}: {}", parent_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        if kind_rank(&parent.kind)? > rank {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let location = state
+        .db
+        .create_physical_location(auth_user.user.id, &request)
+        .await
+        .map_err(map_write_error)?;
+
+    Ok((StatusCode::CREATED, Json(location)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/physical-locations/{id}",
+    tag = "physical_locations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Physical location ID")
+    ),
+    responses(
+        (status = 200, description = "Physical location", body = PhysicalLocation),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Physical location not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_physical_location(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PhysicalLocation>, StatusCode> {
+    let location = state
+        .db
+        .get_physical_location(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get physical location {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(location))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/physical-locations/{id}",
+    tag = "physical_locations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Physical location ID")
+    ),
+    request_body = UpdatePhysicalLocationRequest,
+    responses(
+        (status = 200, description = "Physical location updated", body = PhysicalLocation),
+        (status = 400, description = "Invalid kind or empty name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Physical location not found"),
+        (status = 409, description = "The parent already holds a location with this name"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_physical_location(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdatePhysicalLocationRequest>,
+) -> Result<Json<PhysicalLocation>, StatusCode> {
+    if let Some(kind) = &request.kind {
+        kind_rank(kind)?;
+    }
+    if request.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let location = state
+        .db
+        .update_physical_location(id, auth_user.user.id, &request)
+        .await
+        .map_err(map_write_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(location))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/physical-locations/{id}",
+    tag = "physical_locations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Physical location ID")
+    ),
+    responses(
+        (status = 204, description = "Physical location deleted; its documents no longer have a location"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Physical location not found"),
+        (status = 409, description = "The location still holds other locations"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_physical_location(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let has_sublocations = state.db.has_physical_sublocations(id).await.map_err(|e| {
+        error!("Failed to check sublocations of physical location {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if has_sublocations {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let deleted = state
+        .db
+        .delete_physical_location(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete physical location {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Set or clear the physical location of many documents at once
+#[utoipa::path(
+    post,
+    path = "/api/physical-locations/assign",
+    tag = "physical_locations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = AssignPhysicalLocationRequest,
+    responses(
+        (status = 200, description = "Number of documents updated", body = AssignPhysicalLocationResponse),
+        (status = 400, description = "No documents or more than 1000"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Physical location not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn assign_physical_location(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<AssignPhysicalLocationRequest>,
+) -> Result<Json<AssignPhysicalLocationResponse>, StatusCode> {
+    if request.document_ids.is_empty() || request.document_ids.len() > 1000 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if let Some(location_id) = request.location_id {
+        state
+            .db
+            .get_physical_location(location_id, auth_user.user.id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get physical location {}: {}", location_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+    }
+
+    let updated = state
+        .db
+        .assign_physical_location(auth_user.user.id, &request.document_ids, request.location_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to assign physical location: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(AssignPhysicalLocationResponse { updated }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/physical-locations/{id}/documents",
+    tag = "physical_locations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Physical location ID"),
+        PhysicalLocationDocumentsQuery
+    ),
+    responses(
+        (status = 200, description = "Documents kept in the location, oldest first", body = Vec<DocumentResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Physical location not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_physical_location_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(query): Query<PhysicalLocationDocumentsQuery>,
+) -> Result<Json<Vec<DocumentResponse>>, StatusCode> {
+    state
+        .db
+        .get_physical_location(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get physical location {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let documents = state
+        .db
+        .get_physical_location_documents(
+            auth_user.user.id,
+            id,
+            query.include_sublocations,
+            query.limit.unwrap_or(50).clamp(1, 1000),
+            query.offset.unwrap_or(0).max(0),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to list documents of physical location {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(documents.into_iter().map(DocumentResponse::from).collect()))
+}
+
+/// Document counts, sizes, date ranges and retention status per location
+#[utoipa::path(
+    get,
+    path = "/api/physical-locations/stats",
+    tag = "physical_locations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Statistics per physical location", body = PhysicalLocationStatsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_physical_location_stats(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<PhysicalLocationStatsResponse>, StatusCode> {
+    let user_id = auth_user.user.id;
+    let retention_days = retention_days(&state, user_id).await?;
+
+    let locations = state.db.get_physical_locations(user_id).await.map_err(|e| {
+        error!("Failed to list physical locations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let direct: HashMap<Uuid, _> = state
+        .db
+        .get_physical_location_document_stats(user_id, retention_days)
+        .await
+        .map_err(|e| {
+            error!("Failed to get physical location statistics: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .collect();
+    let unassigned_documents = state
+        .db
+        .count_documents_without_physical_location(user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to count documents without a physical location: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let paths = location_paths(&locations);
+    let mut stats: HashMap<Uuid, PhysicalLocationStats> = locations
+        .iter()
+        .map(|location| {
+            let own = direct.get(&location.id).cloned().unwrap_or_default();
+            (location.id, PhysicalLocationStats {
+                location_id: location.id,
+                kind: location.kind.clone(),
+                path: paths[&location.id].clone(),
+                document_count: own.document_count,
+                total_document_count: 0,
+                total_size_bytes: 0,
+                oldest_document_date: None,
+                newest_document_date: None,
+                due_for_destruction: 0,
+            })
+        })
+        .collect();
+
+    // Every location's documents also count towards each location enclosing it
+    let by_id: HashMap<Uuid, &PhysicalLocation> = locations.iter().map(|l| (l.id, l)).collect();
+    for (location_id, own) in &direct {
+        for enclosing_id in location_chain(&by_id, *location_id) {
+            if let Some(entry) = stats.get_mut(&enclosing_id) {
+                entry.total_document_count += own.document_count;
+                entry.total_size_bytes += own.total_size_bytes;
+                entry.due_for_destruction += own.due_for_destruction;
+                entry.oldest_document_date = entry.oldest_document_date.into_iter().chain(own.oldest_document_date).min();
+                entry.newest_document_date = entry.newest_document_date.into_iter().chain(own.newest_document_date).max();
+            }
+        }
+    }
+
+    let mut locations: Vec<PhysicalLocationStats> = stats.into_values().collect();
+    locations.sort_by(|a, b| a.path.to_lowercase().cmp(&b.path.to_lowercase()));
+
+    Ok(Json(PhysicalLocationStatsResponse {
+        locations,
+        unassigned_documents,
+        retention_days,
+    }))
+}
+
+/// Which locations hold originals past the user's retention period, and which ones
+#[utoipa::path(
+    get,
+    path = "/api/physical-locations/retention-report",
+    tag = "physical_locations",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Documents due for destruction grouped by location", body = RetentionReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_retention_report(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<RetentionReport>, StatusCode> {
+    let user_id = auth_user.user.id;
+    let Some(retention_days) = retention_days(&state, user_id).await? else {
+        return Ok(Json(RetentionReport { retention_days: None, locations: Vec::new() }));
+    };
+
+    let locations = state.db.get_physical_locations(user_id).await.map_err(|e| {
+        error!("Failed to list physical locations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let due = state
+        .db
+        .get_retention_due_documents(user_id, retention_days)
+        .await
+        .map_err(|e| {
+            error!("Failed to get documents due for destruction: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let paths = location_paths(&locations);
+    let mut by_location: HashMap<Uuid, RetentionLocationReport> = HashMap::new();
+    for (location_id, document) in due {
+        by_location
+            .entry(location_id)
+            .or_insert_with(|| RetentionLocationReport {
+                location_id,
+                path: paths.get(&location_id).cloned().unwrap_or_default(),
+                documents: Vec::new(),
+            })
+            .documents
+            .push(document);
+    }
+
+    let mut report: Vec<RetentionLocationReport> = by_location.into_values().collect();
+    report.sort_by(|a, b| a.path.to_lowercase().cmp(&b.path.to_lowercase()));
+
+    Ok(Json(RetentionReport {
+        retention_days: Some(retention_days),
+        locations: report,
+    }))
+}
@@ -134,6 +134,12 @@ pub async fn get_prometheus_metrics(
     writeln!(&mut output, "# HELP readur_db_response_time_ms Database response time in milliseconds").unwrap();
     writeln!(&mut output, "# TYPE readur_db_response_time_ms gauge").unwrap();
     writeln!(&mut output, "readur_db_response_time_ms {} {}", database_metrics.response_time_ms, timestamp).unwrap();
+
+    if let Some(replica_up) = database_metrics.replica_up {
+        writeln!(&mut output, "# HELP readur_db_replica_up Whether heavy reads go to the read replica (1) or fell back to the primary (0)").unwrap();
+        writeln!(&mut output, "# TYPE readur_db_replica_up gauge").unwrap();
+        writeln!(&mut output, "readur_db_replica_up {} {}", u8::from(replica_up), timestamp).unwrap();
+    }
     
     // Enhanced OCR metrics
     if let Some(confidence) = ocr_metrics.avg_confidence {
@@ -156,6 +162,32 @@ pub async fn get_prometheus_metrics(
     writeln!(&mut output, "# TYPE readur_ocr_queue_depth gauge").unwrap();
     writeln!(&mut output, "readur_ocr_queue_depth {} {}", ocr_metrics.queue_depth, timestamp).unwrap();
     
+    // OCR worker metrics for this server
+    let worker = state.queue_service.worker_throughput();
+    let worker_label = format!("worker_id=\"{}\"", worker.worker_id);
+
+    writeln!(&mut output, "# HELP readur_ocr_worker_max_concurrent_jobs Configured concurrent OCR jobs per worker").unwrap();
+    writeln!(&mut output, "# TYPE readur_ocr_worker_max_concurrent_jobs gauge").unwrap();
+    writeln!(&mut output, "readur_ocr_worker_max_concurrent_jobs{{{}}} {} {}", worker_label, worker.max_concurrent_jobs, timestamp).unwrap();
+
+    writeln!(&mut output, "# HELP readur_ocr_worker_active_jobs OCR jobs currently running per worker").unwrap();
+    writeln!(&mut output, "# TYPE readur_ocr_worker_active_jobs gauge").unwrap();
+    writeln!(&mut output, "readur_ocr_worker_active_jobs{{{}}} {} {}", worker_label, worker.active_jobs, timestamp).unwrap();
+
+    writeln!(&mut output, "# HELP readur_ocr_worker_jobs_completed_total OCR jobs completed per worker since startup").unwrap();
+    writeln!(&mut output, "# TYPE readur_ocr_worker_jobs_completed_total counter").unwrap();
+    writeln!(&mut output, "readur_ocr_worker_jobs_completed_total{{{}}} {} {}", worker_label, worker.completed, timestamp).unwrap();
+
+    writeln!(&mut output, "# HELP readur_ocr_worker_jobs_failed_total OCR job attempts failed per worker since startup").unwrap();
+    writeln!(&mut output, "# TYPE readur_ocr_worker_jobs_failed_total counter").unwrap();
+    writeln!(&mut output, "readur_ocr_worker_jobs_failed_total{{{}}} {} {}", worker_label, worker.failed, timestamp).unwrap();
+
+    if let Some(avg_ms) = worker.avg_processing_time_ms {
+        writeln!(&mut output, "# HELP readur_ocr_worker_avg_processing_time_ms Average OCR processing time per worker in milliseconds").unwrap();
+        writeln!(&mut output, "# TYPE readur_ocr_worker_avg_processing_time_ms gauge").unwrap();
+        writeln!(&mut output, "readur_ocr_worker_avg_processing_time_ms{{{}}} {} {}", worker_label, avg_ms, timestamp).unwrap();
+    }
+
     // Storage metrics
     writeln!(&mut output, "# HELP readur_storage_usage_percent Storage utilization percentage").unwrap();
     writeln!(&mut output, "# TYPE readur_storage_usage_percent gauge").unwrap();
@@ -246,6 +278,9 @@ pub async fn get_prometheus_metrics(
     writeln!(&mut output, "# HELP readur_webdav_error_rate_last_hour WebDAV error rate in the last hour (percentage)").unwrap();
     writeln!(&mut output, "# TYPE readur_webdav_error_rate_last_hour gauge").unwrap();
     writeln!(&mut output, "readur_webdav_error_rate_last_hour {} {}", webdav_metrics.error_rate_last_hour, timestamp).unwrap();
+
+    // Request latency, OCR job durations, sync outcomes and LLM tokens since startup
+    crate::monitoring::prometheus::render(&mut output);
     
     // Return the metrics with the correct content type
     Ok((
@@ -287,6 +322,8 @@ struct DatabaseMetrics {
     total_connections: u32,
     utilization_percent: u8,
     response_time_ms: u64,
+    /// Whether reads go to the read replica; None without one
+    replica_up: Option<bool>,
 }
 
 struct SystemMetrics {
@@ -507,6 +544,7 @@ async fn collect_database_metrics(state: &Arc<AppState>) -> Result<DatabaseMetri
         total_connections,
         utilization_percent: utilization,
         response_time_ms: response_time,
+        replica_up: state.db.read_replica().map(|_| state.db.reads_from_replica()),
     })
 }
 
@@ -0,0 +1,268 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{Json, Response},
+    routing::{delete, get},
+    Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{ConverterQuarantineEntry, ConverterQuarantineQuery, QuarantineListQuery, QuarantinedFile},
+    routes::queue::require_admin,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::{converter_sandbox, malware_scan_service},
+    AppState,
+};
+
+const DEFAULT_LIST_LIMIT: i64 = 50;
+const MAX_LIST_LIMIT: i64 = 500;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_quarantined_files))
+        .route("/converters", get(list_converter_quarantine))
+        .route("/converters/{file_hash}", delete(release_converter_quarantine))
+        .route("/{id}", delete(delete_quarantined_file))
+        .route("/{id}/download", get(download_quarantined_file))
+}
+
+/// Files the malware scanner refused, newest first
+#[utoipa::path(
+    get,
+    path = "/api/quarantine",
+    tag = "quarantine",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(QuarantineListQuery),
+    responses(
+        (status = 200, description = "Quarantined files with the signature found in them", body = Vec<QuarantinedFile>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_quarantined_files(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<QuarantineListQuery>,
+) -> Result<Json<Vec<QuarantinedFile>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let files = state.db.list_quarantined_files(limit, offset).await.map_err(|e| {
+        error!("Failed to list quarantined files: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(files))
+}
+
+/// Download a quarantined file for analysis
+///
+/// The file is served as is, never inline; it is infected.
+#[utoipa::path(
+    get,
+    path = "/api/quarantine/{id}/download",
+    tag = "quarantine",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Quarantined file ID")
+    ),
+    responses(
+        (status = 200, description = "The infected file", content_type = "application/octet-stream"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Quarantined file not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn download_quarantined_file(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<Response<Body>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let file = state
+        .db
+        .get_quarantined_file(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get quarantined file {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let data = tokio::fs::read(&file.file_path).await.map_err(|e| {
+        error!("Failed to read quarantined file {}: {}", file.file_path, e);
+        StatusCode::NOT_FOUND
+    })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::QUARANTINE_DOWNLOAD, "quarantined_file", Some(file.id))
+            .details(json!({ "filename": file.original_filename, "signature": file.signature })),
+    )
+    .await;
+
+    let filename = format!("{}.quarantined", file.original_filename.replace(['"', '\\', '/'], "_"));
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .header("Content-Disposition", format!("attachment; filename=\"{}\"", filename))
+        .header("X-Content-Type-Options", "nosniff")
+        .body(Body::from(data))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Delete a quarantined file for good
+#[utoipa::path(
+    delete,
+    path = "/api/quarantine/{id}",
+    tag = "quarantine",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Quarantined file ID")
+    ),
+    responses(
+        (status = 204, description = "Quarantined file deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Quarantined file not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_quarantined_file(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let deleted = malware_scan_service::delete_quarantined(&state.db, id).await.map_err(|e| {
+        error!("Failed to delete quarantined file {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("User {} deleted quarantined file {}", auth_user.user.id, id);
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::QUARANTINE_DELETE, "quarantined_file", Some(id)),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Contents external converters were killed on, most recently killed first
+///
+/// Only quarantined contents are listed unless `include_strikes` is set.
+#[utoipa::path(
+    get,
+    path = "/api/quarantine/converters",
+    tag = "quarantine",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(ConverterQuarantineQuery),
+    responses(
+        (status = 200, description = "Contents with the converter and limit they were killed for", body = Vec<ConverterQuarantineEntry>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_converter_quarantine(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<ConverterQuarantineQuery>,
+) -> Result<Json<Vec<ConverterQuarantineEntry>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let entries = state
+        .db
+        .list_converter_quarantine(query.include_strikes, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to list converter quarantine: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(entries))
+}
+
+/// Let external converters run on a content again and forget its strikes
+///
+/// Documents with the content are not reprocessed; retry their OCR to convert them.
+#[utoipa::path(
+    delete,
+    path = "/api/quarantine/converters/{file_hash}",
+    tag = "quarantine",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("file_hash" = String, Path, description = "SHA-256 of the content")
+    ),
+    responses(
+        (status = 204, description = "Content released"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "No converter was killed on the content"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn release_converter_quarantine(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(file_hash): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let released = state.db.release_converter_quarantine(&file_hash).await.map_err(|e| {
+        error!("Failed to release content {} from converter quarantine: {}", file_hash, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !released {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    converter_sandbox::release(&file_hash);
+
+    info!("User {} released content {} from converter quarantine", auth_user.user.id, file_hash);
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::CONVERTER_QUARANTINE_RELEASE, "converter_quarantine", None)
+            .details(json!({ "file_hash": file_hash })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,14 +1,23 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::Json,
     routing::{get, post},
     Router,
 };
+use serde::Deserialize;
 use sqlx::Row;
+use utoipa::ToSchema;
 use std::{sync::Arc, error::Error};
 
-use crate::{auth::AuthUser, AppState, models::UserRole};
+use crate::{
+    auth::AuthUser,
+    models::{QueueControlStatus, QueueKind, ThumbnailBackfillResponse, ThumbnailQueueStats, UserRole},
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::maintenance_service,
+    services::thumbnail_service::ThumbnailService,
+    AppState,
+};
 
 pub fn require_admin(auth_user: &AuthUser) -> Result<(), StatusCode> {
     if auth_user.user.role != UserRole::Admin {
@@ -26,6 +35,12 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/pause", post(pause_ocr_processing))
         .route("/resume", post(resume_ocr_processing))
         .route("/status", get(get_ocr_status))
+        .route("/controls", get(list_queue_controls))
+        .route("/{queue}/pause", post(pause_queue))
+        .route("/{queue}/resume", post(resume_queue))
+        .route("/workers", get(get_ocr_workers).put(update_ocr_workers))
+        .route("/thumbnails", get(get_thumbnail_queue_stats))
+        .route("/thumbnails/backfill", post(backfill_thumbnails))
 }
 
 #[utoipa::path(
@@ -123,16 +138,18 @@ async fn requeue_failed(
     responses(
         (status = 200, description = "OCR processing paused successfully"),
         (status = 401, description = "Unauthorized"),
-        (status = 403, description = "Forbidden - Admin access required")
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
     )
 )]
 async fn pause_ocr_processing(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     require_admin(&auth_user)?;
     
-    state.queue_service.pause();
+    set_queue_paused(&state, &auth_user, &client, QueueKind::Ocr, true).await?;
     
     Ok(Json(serde_json::json!({
         "status": "paused",
@@ -150,16 +167,18 @@ async fn pause_ocr_processing(
     responses(
         (status = 200, description = "OCR processing resumed successfully"),
         (status = 401, description = "Unauthorized"),
-        (status = 403, description = "Forbidden - Admin access required")
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
     )
 )]
 async fn resume_ocr_processing(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
+    client: ClientInfo,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     require_admin(&auth_user)?;
     
-    state.queue_service.resume();
+    set_queue_paused(&state, &auth_user, &client, QueueKind::Ocr, false).await?;
     
     Ok(Json(serde_json::json!({
         "status": "resumed",
@@ -186,7 +205,7 @@ async fn get_ocr_status(
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     require_admin(&auth_user)?;
     
-    let is_paused = state.queue_service.is_paused();
+    let is_paused = state.queue_service.is_paused() || maintenance_service::holds(QueueKind::Ocr);
     
     Ok(Json(serde_json::json!({
         "is_paused": is_paused,
@@ -194,6 +213,199 @@ async fn get_ocr_status(
     })))
 }
 
+/// Whether the OCR, sync and analysis queues take work (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/queue/controls",
+    tag = "queue",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "State of each queue", body = Vec<QueueControlStatus>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required")
+    )
+)]
+async fn list_queue_controls(auth_user: AuthUser) -> Result<Json<Vec<QueueControlStatus>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let controls = maintenance_service::current();
+    let statuses = [QueueKind::Ocr, QueueKind::Sync, QueueKind::Analysis]
+        .into_iter()
+        .map(|queue| QueueControlStatus {
+            queue,
+            paused: controls.paused(queue),
+            held_for_maintenance: controls.maintenance_mode,
+        })
+        .collect();
+    Ok(Json(statuses))
+}
+
+/// Stop a queue from taking new work on every node; running jobs finish (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/queue/{queue}/pause",
+    tag = "queue",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("queue" = String, Path, description = "`ocr`, `sync` or `analysis`")
+    ),
+    responses(
+        (status = 200, description = "Queue paused", body = QueueControlStatus),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "No such queue"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn pause_queue(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(queue): Path<String>,
+) -> Result<Json<QueueControlStatus>, StatusCode> {
+    require_admin(&auth_user)?;
+    let queue = QueueKind::from_name(&queue).ok_or(StatusCode::NOT_FOUND)?;
+    set_queue_paused(&state, &auth_user, &client, queue, true).await.map(Json)
+}
+
+/// Let a paused queue take work again (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/queue/{queue}/resume",
+    tag = "queue",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("queue" = String, Path, description = "`ocr`, `sync` or `analysis`")
+    ),
+    responses(
+        (status = 200, description = "Queue resumed; it stays held while the maintenance mode is on", body = QueueControlStatus),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "No such queue"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn resume_queue(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(queue): Path<String>,
+) -> Result<Json<QueueControlStatus>, StatusCode> {
+    require_admin(&auth_user)?;
+    let queue = QueueKind::from_name(&queue).ok_or(StatusCode::NOT_FOUND)?;
+    set_queue_paused(&state, &auth_user, &client, queue, false).await.map(Json)
+}
+
+async fn set_queue_paused(
+    state: &AppState,
+    auth_user: &AuthUser,
+    client: &ClientInfo,
+    queue: QueueKind,
+    paused: bool,
+) -> Result<QueueControlStatus, StatusCode> {
+    let controls = state
+        .db
+        .set_queue_paused(queue, paused, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to {} the {} queue: {}", if paused { "pause" } else { "resume" }, queue.as_str(), e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    maintenance_service::set(controls.clone());
+
+    let action = if paused { audit_service::QUEUE_PAUSE } else { audit_service::QUEUE_RESUME };
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        client,
+        AuditEvent::new(action, "queue", None).details(serde_json::json!({ "queue": queue.as_str() })),
+    )
+    .await;
+    tracing::info!(
+        "User {} {} the {} queue",
+        auth_user.user.username,
+        if paused { "paused" } else { "resumed" },
+        queue.as_str()
+    );
+
+    Ok(QueueControlStatus {
+        queue,
+        paused,
+        held_for_maintenance: controls.maintenance_mode,
+    })
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateOcrWorkersRequest {
+    /// Number of OCR jobs to run concurrently on this server
+    pub max_concurrent_jobs: usize,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/queue/workers",
+    tag = "queue",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "OCR worker concurrency and throughput since startup"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required")
+    )
+)]
+async fn get_ocr_workers(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    Ok(Json(serde_json::json!({
+        "max_allowed": crate::ocr::queue::MAX_CONCURRENT_OCR_JOBS,
+        "worker": state.queue_service.worker_throughput(),
+    })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/queue/workers",
+    tag = "queue",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = UpdateOcrWorkersRequest,
+    responses(
+        (status = 200, description = "OCR worker concurrency updated"),
+        (status = 400, description = "Concurrency must be at least 1"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required")
+    )
+)]
+async fn update_ocr_workers(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateOcrWorkersRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    if request.max_concurrent_jobs == 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let applied = state.queue_service.set_max_concurrent_jobs(request.max_concurrent_jobs);
+
+    Ok(Json(serde_json::json!({
+        "max_concurrent_jobs": applied,
+        "max_allowed": crate::ocr::queue::MAX_CONCURRENT_OCR_JOBS,
+    })))
+}
+
 #[utoipa::path(
     post,
     path = "/api/queue/enqueue-pending",
@@ -269,4 +481,64 @@ async fn enqueue_pending_documents(
         "message": format!("Successfully queued {} pending documents for OCR processing", queue_ids.len()),
         "queue_ids": queue_ids
     })))
-}
\ No newline at end of file
+}
+/// Progress of the background thumbnail generation (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/queue/thumbnails",
+    tag = "queue",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Thumbnail jobs by status", body = ThumbnailQueueStats),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_thumbnail_queue_stats(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<ThumbnailQueueStats>, StatusCode> {
+    require_admin(&auth_user)?;
+    let stats = state.db.get_thumbnail_queue_stats().await.map_err(|e| {
+        tracing::error!("Failed to get thumbnail queue stats: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(stats))
+}
+
+/// Queue thumbnails of every document that has none queued, e.g. documents ingested
+/// before thumbnails were generated in the background, and retry failed ones (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/queue/thumbnails/backfill",
+    tag = "queue",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Documents queued for thumbnail generation", body = ThumbnailBackfillResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 409, description = "Thumbnail pre-generation is disabled"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn backfill_thumbnails(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<ThumbnailBackfillResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    if !ThumbnailService::enabled() {
+        return Err(StatusCode::CONFLICT);
+    }
+    let queued = ThumbnailService::backfill(&state.db).await.map_err(|e| {
+        tracing::error!("Failed to queue thumbnail backfill: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(ThumbnailBackfillResponse { queued }))
+}
@@ -0,0 +1,301 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use chrono::Utc;
+use std::sync::Arc;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        CreateReminderRequest, DocumentReminder, ReminderListQuery, SharePermission, SnoozeReminderRequest,
+        UpdateReminderRequest,
+    },
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_reminders))
+        .route("/{id}", put(update_reminder).delete(delete_reminder))
+        .route("/{id}/snooze", post(snooze_reminder))
+        .route("/{id}/complete", post(complete_reminder))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// The current user's reminders, soonest first
+#[utoipa::path(
+    get,
+    path = "/api/reminders",
+    tag = "reminders",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(ReminderListQuery),
+    responses(
+        (status = 200, description = "Reminders of the current user", body = Vec<DocumentReminder>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_reminders(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<ReminderListQuery>,
+) -> Result<Json<Vec<DocumentReminder>>, StatusCode> {
+    let reminders = state
+        .db
+        .get_user_reminders(auth_user.user.id, query.status)
+        .await
+        .map_err(|e| internal_error("Failed to list reminders", e))?;
+
+    Ok(Json(reminders))
+}
+
+/// The current user's reminders on a document
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/reminders",
+    tag = "reminders",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Reminders on the document, soonest first", body = Vec<DocumentReminder>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_document_reminders(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<Vec<DocumentReminder>>, StatusCode> {
+    state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let reminders = state
+        .db
+        .get_document_reminders(document_id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list document reminders", e))?;
+
+    Ok(Json(reminders))
+}
+
+/// Set a reminder on a document for the current user
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/reminders",
+    tag = "reminders",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    request_body = CreateReminderRequest,
+    responses(
+        (status = 201, description = "Reminder set", body = DocumentReminder),
+        (status = 400, description = "Time in the past or note too long"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_document_reminder(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+    Json(request): Json<CreateReminderRequest>,
+) -> Result<(StatusCode, Json<DocumentReminder>), StatusCode> {
+    if let Err(reason) = request.validate(Utc::now()) {
+        debug!("Rejected reminder on document {}: {}", document_id, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    state
+        .db
+        .get_shared_document_by_id(document_id, auth_user.user.id, auth_user.user.role, SharePermission::View)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let note = request.note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+    let reminder = state
+        .db
+        .create_reminder(document_id, auth_user.user.id, request.remind_at, note)
+        .await
+        .map_err(|e| internal_error("Failed to create reminder", e))?;
+
+    Ok((StatusCode::CREATED, Json(reminder)))
+}
+
+/// Change the time or note of a reminder
+///
+/// A new time makes a sent or completed reminder pending again.
+#[utoipa::path(
+    put,
+    path = "/api/reminders/{id}",
+    tag = "reminders",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Reminder ID")
+    ),
+    request_body = UpdateReminderRequest,
+    responses(
+        (status = 200, description = "Updated reminder", body = DocumentReminder),
+        (status = 400, description = "Time in the past or note too long"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Reminder not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_reminder(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateReminderRequest>,
+) -> Result<Json<DocumentReminder>, StatusCode> {
+    if let Err(reason) = request.validate(Utc::now()) {
+        debug!("Rejected update of reminder {}: {}", id, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let reminder = state
+        .db
+        .update_reminder(id, auth_user.user.id, request.remind_at, request.note.as_deref())
+        .await
+        .map_err(|e| internal_error("Failed to update reminder", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(reminder))
+}
+
+/// Put a reminder off; it is sent again at the new time
+#[utoipa::path(
+    post,
+    path = "/api/reminders/{id}/snooze",
+    tag = "reminders",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Reminder ID")
+    ),
+    request_body = SnoozeReminderRequest,
+    responses(
+        (status = 200, description = "Snoozed reminder", body = DocumentReminder),
+        (status = 400, description = "Both or neither of until and minutes, or a time in the past"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Reminder not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn snooze_reminder(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SnoozeReminderRequest>,
+) -> Result<Json<DocumentReminder>, StatusCode> {
+    let until = request.resolve(Utc::now()).map_err(|reason| {
+        debug!("Rejected snooze of reminder {}: {}", id, reason);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let reminder = state
+        .db
+        .snooze_reminder(id, auth_user.user.id, until)
+        .await
+        .map_err(|e| internal_error("Failed to snooze reminder", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(reminder))
+}
+
+/// Mark a reminder as done; it is not sent (again)
+#[utoipa::path(
+    post,
+    path = "/api/reminders/{id}/complete",
+    tag = "reminders",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Reminder ID")
+    ),
+    responses(
+        (status = 200, description = "Completed reminder", body = DocumentReminder),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Reminder not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn complete_reminder(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DocumentReminder>, StatusCode> {
+    let reminder = state
+        .db
+        .complete_reminder(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to complete reminder", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(reminder))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/reminders/{id}",
+    tag = "reminders",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Reminder ID")
+    ),
+    responses(
+        (status = 204, description = "Reminder deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Reminder not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_reminder(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_reminder(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to delete reminder", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
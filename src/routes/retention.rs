@@ -0,0 +1,422 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        normalize_mime_pattern, CreateRetentionPolicyRequest, RetentionActionRecord, RetentionActionsQuery,
+        RetentionPolicy, RetentionReview, RetentionReviewsQuery, UpdateRetentionPolicyRequest, RETENTION_ACTIONS,
+        RETENTION_REVIEW_STATUSES,
+    },
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/policies", get(list_retention_policies).post(create_retention_policy))
+        .route(
+            "/policies/{id}",
+            get(get_retention_policy)
+                .put(update_retention_policy)
+                .delete(delete_retention_policy),
+        )
+        .route("/reviews", get(list_retention_reviews))
+        .route("/reviews/{id}/approve", post(approve_retention_review))
+        .route("/reviews/{id}/exempt", post(exempt_retention_review))
+        .route("/actions", get(list_retention_actions))
+}
+
+/// Check a new or edited policy and normalize its document type
+async fn validate_policy(state: &AppState, policy: &mut RetentionPolicy) -> Result<(), StatusCode> {
+    if policy.name.trim().is_empty()
+        || !RETENTION_ACTIONS.contains(&policy.action.as_str())
+        || policy.retain_days <= 0
+        || policy.grace_days < 0
+        || (policy.label_id.is_none() && policy.mime_type.is_none())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    policy.mime_type = policy
+        .mime_type
+        .as_deref()
+        .map(|mime_type| normalize_mime_pattern(mime_type).ok_or(StatusCode::BAD_REQUEST))
+        .transpose()?;
+
+    if let Some(label_id) = policy.label_id {
+        let visible = state.db.label_visible_to_user(label_id, policy.user_id).await.map_err(|e| {
+            error!("Failed to look up label {}: {}", label_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        if !visible {
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/retention/policies",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Retention policies of the current user", body = Vec<RetentionPolicy>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_retention_policies(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<RetentionPolicy>>, StatusCode> {
+    let policies = state
+        .db
+        .get_retention_policies(auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to list retention policies: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(policies))
+}
+
+/// Create a policy that archives or deletes documents with a label, of a document
+/// type, or both, once they are older than `retain_days`
+#[utoipa::path(
+    post,
+    path = "/api/retention/policies",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateRetentionPolicyRequest,
+    responses(
+        (status = 201, description = "Retention policy created", body = RetentionPolicy),
+        (status = 400, description = "Empty name, unknown action, invalid MIME type or days, or neither a label nor a MIME type"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Label not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_retention_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateRetentionPolicyRequest>,
+) -> Result<(StatusCode, Json<RetentionPolicy>), StatusCode> {
+    let now = Utc::now();
+    let mut policy = RetentionPolicy {
+        id: Uuid::nil(),
+        user_id: auth_user.user.id,
+        name: request.name,
+        label_id: request.label_id,
+        mime_type: request.mime_type,
+        action: request.action,
+        retain_days: request.retain_days,
+        grace_days: request.grace_days.unwrap_or(30),
+        enabled: request.enabled.unwrap_or(true),
+        created_at: now,
+        updated_at: now,
+    };
+    validate_policy(&state, &mut policy).await?;
+
+    let policy = state.db.create_retention_policy(&policy).await.map_err(|e| {
+        error!("Failed to create retention policy: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!("User {} created retention policy '{}' ({})", auth_user.user.id, policy.name, policy.id);
+    Ok((StatusCode::CREATED, Json(policy)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/retention/policies/{id}",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Retention policy ID")
+    ),
+    responses(
+        (status = 200, description = "Retention policy", body = RetentionPolicy),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Retention policy not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_retention_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RetentionPolicy>, StatusCode> {
+    let policy = state
+        .db
+        .get_retention_policy(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get retention policy {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(policy))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/retention/policies/{id}",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Retention policy ID")
+    ),
+    request_body = UpdateRetentionPolicyRequest,
+    responses(
+        (status = 200, description = "Retention policy updated", body = RetentionPolicy),
+        (status = 400, description = "Empty name, unknown action, or invalid MIME type or days"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Retention policy or label not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_retention_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateRetentionPolicyRequest>,
+) -> Result<Json<RetentionPolicy>, StatusCode> {
+    let mut policy = state
+        .db
+        .get_retention_policy(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get retention policy {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    policy.name = request.name.unwrap_or(policy.name);
+    policy.label_id = request.label_id.or(policy.label_id);
+    policy.mime_type = request.mime_type.or(policy.mime_type);
+    policy.action = request.action.unwrap_or(policy.action);
+    policy.retain_days = request.retain_days.unwrap_or(policy.retain_days);
+    policy.grace_days = request.grace_days.unwrap_or(policy.grace_days);
+    policy.enabled = request.enabled.unwrap_or(policy.enabled);
+    validate_policy(&state, &mut policy).await?;
+
+    let policy = state
+        .db
+        .update_retention_policy(&policy)
+        .await
+        .map_err(|e| {
+            error!("Failed to update retention policy {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(policy))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/retention/policies/{id}",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Retention policy ID")
+    ),
+    responses(
+        (status = 204, description = "Retention policy and its queued deletions removed; its action records are kept"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Retention policy not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_retention_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_retention_policy(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete retention policy {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Documents queued for deletion by retention policies, soonest first
+#[utoipa::path(
+    get,
+    path = "/api/retention/reviews",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(RetentionReviewsQuery),
+    responses(
+        (status = 200, description = "Queued deletions in the requested state", body = Vec<RetentionReview>),
+        (status = 400, description = "Unknown status"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_retention_reviews(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<RetentionReviewsQuery>,
+) -> Result<Json<Vec<RetentionReview>>, StatusCode> {
+    let status = query.status.as_deref().unwrap_or("pending");
+    if !RETENTION_REVIEW_STATUSES.contains(&status) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let reviews = state
+        .db
+        .get_retention_reviews(auth_user.user.id, status, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to list retention reviews: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(reviews))
+}
+
+async fn set_review_status(
+    state: &AppState,
+    auth_user: &AuthUser,
+    id: Uuid,
+    status: &str,
+) -> Result<StatusCode, StatusCode> {
+    let updated = state
+        .db
+        .set_retention_review_status(id, auth_user.user.id, status)
+        .await
+        .map_err(|e| {
+            error!("Failed to update retention review {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !updated {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    info!("User {} marked retention review {} as {}", auth_user.user.id, id, status);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delete a queued document at the next run instead of waiting for the grace period
+#[utoipa::path(
+    post,
+    path = "/api/retention/reviews/{id}/approve",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Retention review ID")
+    ),
+    responses(
+        (status = 204, description = "Deletion approved"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Retention review not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn approve_retention_review(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    set_review_status(&state, &auth_user, id, "approved").await
+}
+
+/// Keep a queued document; the policy will not queue it again
+#[utoipa::path(
+    post,
+    path = "/api/retention/reviews/{id}/exempt",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Retention review ID")
+    ),
+    responses(
+        (status = 204, description = "Document exempted from the policy"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Retention review not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn exempt_retention_review(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    set_review_status(&state, &auth_user, id, "exempted").await
+}
+
+/// Archivals and deletions carried out by retention policies, newest first
+#[utoipa::path(
+    get,
+    path = "/api/retention/actions",
+    tag = "retention",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(RetentionActionsQuery),
+    responses(
+        (status = 200, description = "Recorded retention actions", body = Vec<RetentionActionRecord>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_retention_actions(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<RetentionActionsQuery>,
+) -> Result<Json<Vec<RetentionActionRecord>>, StatusCode> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let actions = state
+        .db
+        .get_retention_actions(auth_user.user.id, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to list retention actions: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(actions))
+}
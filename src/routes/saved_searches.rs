@@ -0,0 +1,246 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{CreateSavedSearchRequest, SavedSearch, SavedSearchQuery, SearchMode, UpdateSavedSearchRequest},
+    services::saved_search_service::normalize_name,
+    utils::search_query,
+    AppState,
+};
+
+/// Same limit as `/api/search`
+const MAX_QUERY_LENGTH: usize = 1000;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_saved_searches).post(create_saved_search))
+        .route(
+            "/{id}",
+            get(get_saved_search)
+                .put(update_saved_search)
+                .delete(delete_saved_search),
+        )
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+fn validate_search(search: &SavedSearchQuery, notify: bool) -> Result<(), StatusCode> {
+    if search.query.len() > MAX_QUERY_LENGTH {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let uses_query_language = matches!(search.search_mode, None | Some(SearchMode::Simple));
+    if uses_query_language && search_query::parse(&search.query).is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // A search without query or filters would notify about every new document
+    if notify && search.is_unrestricted() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/saved_searches",
+    tag = "saved_searches",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Saved searches of the current user", body = Vec<SavedSearch>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_saved_searches(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<SavedSearch>>, StatusCode> {
+    let saved_searches = state
+        .db
+        .get_saved_searches(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list saved searches", e))?;
+
+    Ok(Json(saved_searches))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/saved_searches",
+    tag = "saved_searches",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateSavedSearchRequest,
+    responses(
+        (status = 201, description = "Saved search created", body = SavedSearch),
+        (status = 400, description = "Invalid name or query, or notifications on a search without query or filters"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "A saved search with this name already exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_saved_search(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateSavedSearchRequest>,
+) -> Result<(StatusCode, Json<SavedSearch>), StatusCode> {
+    let name = normalize_name(&request.name).ok_or(StatusCode::BAD_REQUEST)?;
+    validate_search(&request.search, request.notify.unwrap_or(false))?;
+
+    let taken = state
+        .db
+        .saved_search_name_taken(auth_user.user.id, &name, None)
+        .await
+        .map_err(|e| internal_error("Failed to check saved search name", e))?;
+    if taken {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let saved_search = state
+        .db
+        .create_saved_search(auth_user.user.id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to create saved search", e))?;
+
+    Ok((StatusCode::CREATED, Json(saved_search)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/saved_searches/{id}",
+    tag = "saved_searches",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Saved search ID")
+    ),
+    responses(
+        (status = 200, description = "Saved search", body = SavedSearch),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Saved search not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_saved_search(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SavedSearch>, StatusCode> {
+    let saved_search = state
+        .db
+        .get_saved_search(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to get saved search", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(saved_search))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/saved_searches/{id}",
+    tag = "saved_searches",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Saved search ID")
+    ),
+    request_body = UpdateSavedSearchRequest,
+    responses(
+        (status = 200, description = "Saved search updated", body = SavedSearch),
+        (status = 400, description = "Invalid name or query, or notifications on a search without query or filters"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Saved search not found"),
+        (status = 409, description = "A saved search with this name already exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_saved_search(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateSavedSearchRequest>,
+) -> Result<Json<SavedSearch>, StatusCode> {
+    let existing = state
+        .db
+        .get_saved_search(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to get saved search", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Validate the search as it will be after the update
+    let search = request.search.as_ref().unwrap_or(&existing.search.0);
+    validate_search(search, request.notify.unwrap_or(existing.notify))?;
+
+    if let Some(name) = &request.name {
+        let name = normalize_name(name).ok_or(StatusCode::BAD_REQUEST)?;
+        let taken = state
+            .db
+            .saved_search_name_taken(auth_user.user.id, &name, Some(id))
+            .await
+            .map_err(|e| internal_error("Failed to check saved search name", e))?;
+        if taken {
+            return Err(StatusCode::CONFLICT);
+        }
+    }
+
+    let saved_search = state
+        .db
+        .update_saved_search(id, auth_user.user.id, &request)
+        .await
+        .map_err(|e| internal_error("Failed to update saved search", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(saved_search))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/saved_searches/{id}",
+    tag = "saved_searches",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Saved search ID")
+    ),
+    responses(
+        (status = 204, description = "Saved search deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Saved search not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_saved_search(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_saved_search(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to delete saved search", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
@@ -0,0 +1,529 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, patch, post},
+    Router,
+};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::{AuthUser, ScanBridgeAuth},
+    models::{
+        is_valid_bridge_name, ApproveScanBridgeRequest, PendingScanBridgePairing, ScanBridge, ScanBridgeCredentials,
+        ScanBridgePairing, ScanBridgeTokenRequest, ScanPushMetadata, StartScanBridgePairingRequest,
+        UpdateScanBridgeRequest,
+    },
+    routes::documents::crud::{ingest_upload, DocumentError, UploadedFile},
+    routes::documents::types::DocumentUploadResponse,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::capture_service::{self, UnusablePhoto},
+    services::scan_bridge_service::{self, MAX_BRIDGES_PER_USER, MAX_PUSH_PAGES},
+    AppState,
+};
+
+/// Key of a document's metadata the push details are kept under
+pub const SCAN_BRIDGE_METADATA_KEY: &str = "scan_bridge";
+/// Name of a bridge that did not suggest one
+const DEFAULT_BRIDGE_NAME: &str = "Scanner bridge";
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_scan_bridges))
+        .route("/pair", post(start_scan_bridge_pairing))
+        .route("/token", post(collect_scan_bridge_token))
+        .route("/pairings/{user_code}", get(get_scan_bridge_pairing))
+        .route("/approve", post(approve_scan_bridge))
+        .route("/me", get(get_current_scan_bridge))
+        .route("/push", post(push_scan))
+        .route("/{id}", patch(update_scan_bridge).delete(delete_scan_bridge))
+}
+
+/// List the current user's scanner bridges
+#[utoipa::path(
+    get,
+    path = "/api/scan-bridges",
+    tag = "scan_bridges",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Scanner bridges of the current user, newest first", body = Vec<ScanBridge>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_scan_bridges(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<ScanBridge>>, StatusCode> {
+    let bridges = state.db.get_scan_bridges(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to list scanner bridges of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(bridges))
+}
+
+/// Start pairing a scanner bridge
+///
+/// Called by the bridge app, without authentication. The bridge shows `user_code` to
+/// the user, who approves it in readur, and polls `POST /api/scan-bridges/token` with
+/// `device_code` for its token.
+#[utoipa::path(
+    post,
+    path = "/api/scan-bridges/pair",
+    tag = "scan_bridges",
+    request_body = StartScanBridgePairingRequest,
+    responses(
+        (status = 200, description = "Pairing started", body = ScanBridgePairing),
+        (status = 400, description = "Name, platform or version too long"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn start_scan_bridge_pairing(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(request): Json<StartScanBridgePairingRequest>,
+) -> Result<Json<ScanBridgePairing>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected scanner bridge pairing: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let name = request
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DEFAULT_BRIDGE_NAME);
+    let device_code = scan_bridge_service::generate_device_code();
+    let user_code = scan_bridge_service::generate_user_code();
+    let expires_at = Utc::now() + Duration::minutes(scan_bridge_service::PAIRING_MINUTES);
+    state
+        .db
+        .create_scan_bridge_pairing(
+            &scan_bridge_service::hash_secret(&device_code),
+            &user_code,
+            name,
+            request.platform.as_deref(),
+            request.version.as_deref(),
+            client.ip_address.as_deref(),
+            expires_at,
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to start scanner bridge pairing: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(ScanBridgePairing {
+        device_code,
+        user_code,
+        verification_path: scan_bridge_service::VERIFICATION_PATH.to_string(),
+        expires_in: scan_bridge_service::PAIRING_MINUTES * 60,
+        interval: scan_bridge_service::POLL_INTERVAL_SECONDS,
+    }))
+}
+
+/// Collect the token of an approved pairing
+///
+/// Called by the bridge app every `interval` seconds until the user approved the code.
+/// The token is returned once; the pairing is used up.
+#[utoipa::path(
+    post,
+    path = "/api/scan-bridges/token",
+    tag = "scan_bridges",
+    request_body = ScanBridgeTokenRequest,
+    responses(
+        (status = 200, description = "Pairing approved; the bridge and its token", body = ScanBridgeCredentials),
+        (status = 202, description = "Not approved yet, poll again"),
+        (status = 404, description = "Unknown or expired device code, or the token was collected"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn collect_scan_bridge_token(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ScanBridgeTokenRequest>,
+) -> Result<Response, StatusCode> {
+    let device_code_hash = scan_bridge_service::hash_secret(request.device_code.trim());
+    let approved = state
+        .db
+        .scan_bridge_pairing_approved(&device_code_hash)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up scanner bridge pairing: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if !approved {
+        return Ok(StatusCode::ACCEPTED.into_response());
+    }
+
+    let (token, prefix) = scan_bridge_service::generate_token();
+    let bridge = state
+        .db
+        .complete_scan_bridge_pairing(&device_code_hash, &scan_bridge_service::hash_secret(&token), &prefix)
+        .await
+        .map_err(|e| {
+            error!("Failed to complete scanner bridge pairing: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    info!("Scanner bridge {} of user {} collected its token", bridge.id, bridge.user_id);
+    Ok(Json(ScanBridgeCredentials { bridge, token }).into_response())
+}
+
+/// Show the bridge a pairing code belongs to, before approving it
+#[utoipa::path(
+    get,
+    path = "/api/scan-bridges/pairings/{user_code}",
+    tag = "scan_bridges",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("user_code" = String, Path, description = "Pairing code shown by the bridge")
+    ),
+    responses(
+        (status = 200, description = "The pairing", body = PendingScanBridgePairing),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Unknown or expired code"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_scan_bridge_pairing(
+    State(state): State<Arc<AppState>>,
+    _auth_user: AuthUser,
+    Path(user_code): Path<String>,
+) -> Result<Json<PendingScanBridgePairing>, StatusCode> {
+    let user_code = scan_bridge_service::normalize_user_code(&user_code).ok_or(StatusCode::NOT_FOUND)?;
+    let pairing = state
+        .db
+        .get_scan_bridge_pairing(&user_code)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up scanner bridge pairing: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(pairing))
+}
+
+/// Approve a pairing code shown by a scanner bridge
+///
+/// The bridge then collects a token that pushes documents as the current user.
+#[utoipa::path(
+    post,
+    path = "/api/scan-bridges/approve",
+    tag = "scan_bridges",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = ApproveScanBridgeRequest,
+    responses(
+        (status = 200, description = "Pairing approved", body = PendingScanBridgePairing),
+        (status = 400, description = "Empty or too long name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Unknown, expired or already approved code"),
+        (status = 409, description = "The user already has the maximum number of bridges"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn approve_scan_bridge(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<ApproveScanBridgeRequest>,
+) -> Result<Json<PendingScanBridgePairing>, StatusCode> {
+    if request.name.as_deref().is_some_and(|name| !is_valid_bridge_name(name)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let user_code = scan_bridge_service::normalize_user_code(&request.user_code).ok_or(StatusCode::NOT_FOUND)?;
+
+    let existing = state.db.count_scan_bridges(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to count scanner bridges of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if existing >= MAX_BRIDGES_PER_USER {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let pairing = state
+        .db
+        .approve_scan_bridge_pairing(&user_code, auth_user.user.id, request.name.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Failed to approve scanner bridge pairing: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SCAN_BRIDGE_PAIR, "scan_bridge", None).details(json!({
+            "name": pairing.name,
+            "platform": pairing.platform,
+            "version": pairing.version,
+            "address": pairing.address,
+        })),
+    )
+    .await;
+
+    info!("User {} approved scanner bridge pairing '{}'", auth_user.user.id, pairing.name);
+    Ok(Json(pairing))
+}
+
+/// Rename, disable or enable a scanner bridge
+#[utoipa::path(
+    patch,
+    path = "/api/scan-bridges/{id}",
+    tag = "scan_bridges",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Scanner bridge ID")
+    ),
+    request_body = UpdateScanBridgeRequest,
+    responses(
+        (status = 200, description = "Bridge updated", body = ScanBridge),
+        (status = 400, description = "Empty or too long name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Bridge not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_scan_bridge(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateScanBridgeRequest>,
+) -> Result<Json<ScanBridge>, StatusCode> {
+    if request.name.as_deref().is_some_and(|name| !is_valid_bridge_name(name)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let bridge = state
+        .db
+        .update_scan_bridge(id, auth_user.user.id, request.name.as_deref(), request.enabled)
+        .await
+        .map_err(|e| {
+            error!("Failed to update scanner bridge {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SCAN_BRIDGE_UPDATE, "scan_bridge", Some(bridge.id))
+            .details(json!({ "name": bridge.name, "enabled": bridge.enabled })),
+    )
+    .await;
+
+    Ok(Json(bridge))
+}
+
+/// Remove a scanner bridge; its token stops working
+#[utoipa::path(
+    delete,
+    path = "/api/scan-bridges/{id}",
+    tag = "scan_bridges",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Scanner bridge ID")
+    ),
+    responses(
+        (status = 204, description = "Bridge removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Bridge not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_scan_bridge(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state.db.delete_scan_bridge(id, auth_user.user.id).await.map_err(|e| {
+        error!("Failed to delete scanner bridge {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SCAN_BRIDGE_DELETE, "scan_bridge", Some(id)),
+    )
+    .await;
+
+    info!("User {} removed scanner bridge {}", auth_user.user.id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The bridge a token belongs to, for the bridge app to check its pairing
+#[utoipa::path(
+    get,
+    path = "/api/scan-bridges/me",
+    tag = "scan_bridges",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The bridge", body = ScanBridge),
+        (status = 401, description = "Missing, unknown or disabled bridge token")
+    )
+)]
+pub async fn get_current_scan_bridge(bridge_auth: ScanBridgeAuth) -> Json<ScanBridge> {
+    Json(bridge_auth.bridge)
+}
+
+/// Push scanned pages from a scanner bridge
+///
+/// Authenticated with the bridge's token. The multipart body has one `page` part per
+/// scanned image, in page order, and an optional `metadata` part with the JSON of
+/// `ScanPushMetadata`. The pages are assembled into a searchable PDF that is ingested
+/// like any upload of the bridge's owner.
+#[utoipa::path(
+    post,
+    path = "/api/scan-bridges/push",
+    tag = "scan_bridges",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body(content = String, description = "`page` images in order and optional `metadata` JSON", content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Pages assembled and uploaded", body = DocumentUploadResponse),
+        (status = 400, description = "No pages, too many, a page that cannot be read, or invalid metadata"),
+        (status = 401, description = "Missing, unknown or disabled bridge token"),
+        (status = 413, description = "The pages or the assembled PDF are too large, or it does not fit in the user's storage quota"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn push_scan(
+    State(state): State<Arc<AppState>>,
+    bridge_auth: ScanBridgeAuth,
+    client: ClientInfo,
+    mut multipart: Multipart,
+) -> Result<Json<DocumentUploadResponse>, DocumentError> {
+    let ScanBridgeAuth { bridge, auth_user } = bridge_auth;
+    let mut pages = Vec::new();
+    let mut metadata = ScanPushMetadata::default();
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| DocumentError::BadRequest(format!("Failed to get multipart field: {}", e)))?
+    {
+        match field.name().unwrap_or("") {
+            "page" | "pages" | "pages[]" => {
+                if pages.len() == MAX_PUSH_PAGES {
+                    return Err(DocumentError::BadRequest(format!("A push may have at most {} pages", MAX_PUSH_PAGES)));
+                }
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| DocumentError::BadRequest(format!("Failed to read page image: {}", e)))?;
+                pages.push(data.to_vec());
+            }
+            "metadata" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|_| DocumentError::BadRequest("Failed to read metadata field".to_string()))?;
+                metadata = serde_json::from_str(&text)
+                    .map_err(|e| DocumentError::BadRequest(format!("Invalid push metadata: {}", e)))?;
+            }
+            _ => {}
+        }
+    }
+
+    if pages.is_empty() {
+        return Err(DocumentError::BadRequest("No pages found in the push".to_string()));
+    }
+    metadata.validate(pages.len()).map_err(DocumentError::BadRequest)?;
+    let ocr_language = metadata
+        .ocr_language
+        .as_deref()
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+        .map(|language| {
+            crate::ocr::health::OcrHealthChecker::new()
+                .validate_language(language)
+                .map(|_| language.to_string())
+                .map_err(|e| DocumentError::BadRequest(format!("Invalid OCR language '{}': {}", language, e)))
+        })
+        .transpose()?;
+
+    // The text layer is recognized in the language the document will be OCRed in
+    let language = match &ocr_language {
+        Some(language) => language.clone(),
+        None => state
+            .db
+            .get_user_settings(auth_user.user.id)
+            .await
+            .ok()
+            .flatten()
+            .map(|settings| settings.ocr_language)
+            .unwrap_or_else(|| "eng".to_string()),
+    };
+
+    let page_count = pages.len();
+    let capture = scan_bridge_service::capture_metadata(&metadata);
+    let pdf = capture_service::assemble(pages, &capture, &language).await.map_err(|e| {
+        if let Some(unusable) = e.downcast_ref::<UnusablePhoto>() {
+            return DocumentError::BadRequest(unusable.to_string());
+        }
+        let message = format!("Failed to assemble scan: {}", e);
+        error!("{}", message);
+        DocumentError::InternalServerError(message)
+    })?;
+
+    let client = ClientInfo {
+        user_agent: Some(format!("Scanner bridge: {}", bridge.name)),
+        ..client
+    };
+    let file = UploadedFile {
+        filename: capture.filename.unwrap_or_default(),
+        content_type: "application/pdf".to_string(),
+        data: pdf,
+    };
+    let uploaded = ingest_upload(&state, &auth_user, &client, file, ocr_language, Vec::new()).await?;
+
+    if uploaded.status == "success" {
+        let details = json!({
+            "bridge_id": bridge.id,
+            "bridge": bridge.name,
+            "scanner": metadata.scanner,
+            "pages": page_count,
+            "scanned_at": metadata.scanned_at,
+        });
+        if let Err(e) = state.db.set_document_metadata_value(uploaded.id, SCAN_BRIDGE_METADATA_KEY, details).await {
+            warn!("Failed to record scan details of document {}: {}", uploaded.id, e);
+        }
+        if let Err(e) = state.db.count_scan_bridge_document(bridge.id, page_count as i64).await {
+            warn!("Failed to count document of scanner bridge {}: {}", bridge.id, e);
+        }
+    }
+
+    info!("Scanner bridge '{}' pushed {} page(s) as document {}", bridge.name, page_count, uploaded.id);
+    Ok(Json(uploaded))
+}
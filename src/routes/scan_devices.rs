@@ -0,0 +1,276 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, patch, post},
+    Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{CreateScanDeviceRequest, ScanDevice, ScanDeviceCredentials, UpdateScanDeviceRequest},
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::ftp_drop_service,
+    AppState,
+};
+
+/// Devices one user may register
+const MAX_DEVICES_PER_USER: usize = 50;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_scan_devices).post(create_scan_device))
+        .route("/{id}", patch(update_scan_device).delete(delete_scan_device))
+        .route("/{id}/password", post(reset_scan_device_password))
+}
+
+fn is_valid_device_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty() && name.len() <= 100
+}
+
+/// Credentials to hand out with a new password
+fn credentials(device: ScanDevice, password: String) -> ScanDeviceCredentials {
+    let ftp_address = ftp_drop_service::configured().ok().flatten().map(|config| ftp_drop_service::advertised_address(&config));
+    ScanDeviceCredentials { device, password, ftp_address }
+}
+
+/// List the current user's scan devices
+#[utoipa::path(
+    get,
+    path = "/api/scan-devices",
+    tag = "scan_devices",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Scan devices of the current user, newest first", body = Vec<ScanDevice>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_scan_devices(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<ScanDevice>>, StatusCode> {
+    let devices = state.db.get_scan_devices(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to list scan devices of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(devices))
+}
+
+/// Register a scan device for the FTP scan drop
+///
+/// The response holds the only copy of the generated password; the server keeps just
+/// its hash. Files the device stores are uploaded as the current user.
+#[utoipa::path(
+    post,
+    path = "/api/scan-devices",
+    tag = "scan_devices",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateScanDeviceRequest,
+    responses(
+        (status = 201, description = "Device registered", body = ScanDeviceCredentials),
+        (status = 400, description = "Empty or too long name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "The user already has the maximum number of devices"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_scan_device(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<CreateScanDeviceRequest>,
+) -> Result<(StatusCode, Json<ScanDeviceCredentials>), StatusCode> {
+    if !is_valid_device_name(&request.name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let existing = state.db.get_scan_devices(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to list scan devices of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if existing.len() >= MAX_DEVICES_PER_USER {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let (username, password) = ftp_drop_service::generate_credentials();
+    let device = state
+        .db
+        .create_scan_device(
+            auth_user.user.id,
+            request.name.trim(),
+            &username,
+            &ftp_drop_service::hash_password(&password),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create scan device for user {}: {}", auth_user.user.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SCAN_DEVICE_CREATE, "scan_device", Some(device.id))
+            .details(json!({ "name": device.name, "username": device.username })),
+    )
+    .await;
+
+    info!("User {} registered scan device {}", auth_user.user.id, device.id);
+    Ok((StatusCode::CREATED, Json(credentials(device, password))))
+}
+
+/// Rename, disable or enable a scan device
+#[utoipa::path(
+    patch,
+    path = "/api/scan-devices/{id}",
+    tag = "scan_devices",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Scan device ID")
+    ),
+    request_body = UpdateScanDeviceRequest,
+    responses(
+        (status = 200, description = "Device updated", body = ScanDevice),
+        (status = 400, description = "Empty or too long name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Device not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_scan_device(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateScanDeviceRequest>,
+) -> Result<Json<ScanDevice>, StatusCode> {
+    if request.name.as_deref().is_some_and(|name| !is_valid_device_name(name)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let device = state
+        .db
+        .update_scan_device(id, auth_user.user.id, request.name.as_deref(), request.enabled)
+        .await
+        .map_err(|e| {
+            error!("Failed to update scan device {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SCAN_DEVICE_UPDATE, "scan_device", Some(device.id))
+            .details(json!({ "name": device.name, "enabled": device.enabled })),
+    )
+    .await;
+
+    Ok(Json(device))
+}
+
+/// Give a scan device a new password; the old one stops working
+#[utoipa::path(
+    post,
+    path = "/api/scan-devices/{id}/password",
+    tag = "scan_devices",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Scan device ID")
+    ),
+    responses(
+        (status = 200, description = "New password set", body = ScanDeviceCredentials),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Device not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn reset_scan_device_password(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScanDeviceCredentials>, StatusCode> {
+    let (_, password) = ftp_drop_service::generate_credentials();
+    let device = state
+        .db
+        .set_scan_device_password(id, auth_user.user.id, &ftp_drop_service::hash_password(&password))
+        .await
+        .map_err(|e| {
+            error!("Failed to reset password of scan device {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SCAN_DEVICE_PASSWORD_RESET, "scan_device", Some(device.id)),
+    )
+    .await;
+
+    info!("User {} reset the password of scan device {}", auth_user.user.id, device.id);
+    Ok(Json(credentials(device, password)))
+}
+
+/// Remove a scan device; it can no longer sign in
+#[utoipa::path(
+    delete,
+    path = "/api/scan-devices/{id}",
+    tag = "scan_devices",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Scan device ID")
+    ),
+    responses(
+        (status = 204, description = "Device removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Device not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_scan_device(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state.db.delete_scan_device(id, auth_user.user.id).await.map_err(|e| {
+        error!("Failed to delete scan device {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SCAN_DEVICE_DELETE, "scan_device", Some(id)),
+    )
+    .await;
+
+    info!("User {} removed scan device {}", auth_user.user.id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
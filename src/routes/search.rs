@@ -1,16 +1,31 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
-    routing::get,
+    routing::{delete, get, post, put},
     Router,
 };
 use std::sync::Arc;
+use uuid::Uuid;
 
 use crate::{
     auth::AuthUser,
     errors::search::SearchError,
-    models::{SearchRequest, SearchResponse, EnhancedDocumentResponse, SearchFacetsResponse},
+    monitoring::slow_operations::{self, OperationCategory},
+    models::{
+        CreateSearchSynonymRequest, ExternalSearchStatus, SearchAnalyzer,
+        SearchAnalyzerUpdateResponse, SearchFacetsParams, SearchFacetsResponse, SearchIndexRebuild, SearchMode,
+        SearchReindexQuery, SearchRequest, SearchResponse, SearchResultFacets, SearchSynonym, SearchSynonymsQuery,
+        UpdateSearchAnalyzerRequest, UserRole, SEARCH_LANGUAGES,
+    },
+    routes::queue::require_admin,
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        external_search::{self, indexer::ExternalSearchIndexer},
+        search_backend::{self, SearchQuery},
+        search_index_service::SearchIndexService,
+    },
+    utils::{geo, search_query},
     AppState,
 };
 
@@ -19,6 +34,14 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/", get(search_documents))
         .route("/enhanced", get(enhanced_search_documents))
         .route("/facets", get(get_search_facets))
+        .route("/reindex", get(list_reindex_runs).post(reindex_search))
+        .route("/reindex/{id}", get(get_reindex_run))
+        .route("/analyzers", get(list_search_analyzers))
+        .route("/analyzers/{language}", put(update_search_analyzer))
+        .route("/synonyms", get(list_search_synonyms).post(set_search_synonym))
+        .route("/synonyms/{id}", delete(delete_search_synonym))
+        .route("/external/status", get(get_external_search_status))
+        .route("/external/resync", post(resync_external_search))
 }
 
 #[utoipa::path(
@@ -50,6 +73,9 @@ async fn search_documents(
     if search_request.query.len() > 1000 {
         return Err(SearchError::query_too_long(search_request.query.len(), 1000));
     }
+    search_query::parse(&search_request.query).map_err(|e| SearchError::invalid_syntax(e.to_string()))?;
+    geo::location_filters(search_request.near.as_deref(), search_request.bbox.as_deref())
+        .map_err(|e| SearchError::invalid_syntax(e.to_string()))?;
     
     // Validate pagination
     let limit = search_request.limit.unwrap_or(25);
@@ -57,45 +83,67 @@ async fn search_documents(
     if limit > 1000 || offset < 0 || limit <= 0 {
         return Err(SearchError::invalid_pagination(offset, limit));
     }
-    
-    let documents = state
-        .db
-        .search_documents(auth_user.user.id, &search_request)
-        .await
-        .map_err(|e| SearchError::index_unavailable(format!("Search failed: {}", e)))?;
-    
-    let total = documents.len() as i64;
-    
+
+    let start_time = std::time::Instant::now();
+    let query = SearchQuery {
+        user_id: auth_user.user.id,
+        role: UserRole::User,
+        request: &search_request,
+        limit,
+        offset,
+        enhanced: false,
+    };
+    let (engine, results) = search_backend::search(&state.db, &query).await;
+    let total = results.as_ref().ok().map(|results| results.total);
+    record_search("search.basic", start_time, auth_user.user.id, &search_request, engine, total);
+    let results = results.map_err(|e| SearchError::index_unavailable(format!("Search failed: {}", e)))?;
+
     // Check if too many results
-    if total > 10000 {
-        return Err(SearchError::too_many_results(total, 10000));
+    let found = results.documents.len() as i64;
+    if found > 10000 {
+        return Err(SearchError::too_many_results(found, 10000));
     }
 
     let response = SearchResponse {
-        documents: documents.into_iter().map(|doc| EnhancedDocumentResponse {
-            id: doc.id,
-            filename: doc.filename,
-            original_filename: doc.original_filename,
-            file_size: doc.file_size,
-            mime_type: doc.mime_type,
-            tags: doc.tags,
-            created_at: doc.created_at,
-            has_ocr_text: doc.ocr_text.is_some(),
-            ocr_confidence: doc.ocr_confidence,
-            ocr_word_count: doc.ocr_word_count,
-            ocr_processing_time_ms: doc.ocr_processing_time_ms,
-            ocr_status: doc.ocr_status,
-            search_rank: None,
-            snippets: Vec::new(),
-        }).collect(),
-        total,
-        query_time_ms: 0,
+        documents: results.documents,
+        total: results.total,
+        query_time_ms: start_time.elapsed().as_millis() as u64,
         suggestions: Vec::new(),
+        facets: None,
     };
 
     Ok(Json(response))
 }
 
+/// Add a finished search to the slow operation profiler; `results` is None when it failed
+fn record_search(
+    name: &str,
+    started: std::time::Instant,
+    user_id: Uuid,
+    request: &SearchRequest,
+    engine: &str,
+    results: Option<i64>,
+) {
+    slow_operations::record(
+        OperationCategory::Search,
+        name,
+        started,
+        results.is_some(),
+        serde_json::json!({
+            "query": request.query,
+            "user_id": user_id,
+            "engine": engine,
+            "tags": request.tags,
+            "mime_types": request.mime_types,
+            "near": request.near,
+            "bbox": request.bbox,
+            "limit": request.limit,
+            "offset": request.offset,
+            "results": results,
+        }),
+    );
+}
+
 #[utoipa::path(
     get,
     path = "/api/search/enhanced",
@@ -105,10 +153,12 @@ async fn search_documents(
         ("bearer_auth" = [])
     ),
     params(
-        SearchRequest
+        SearchRequest,
+        SearchFacetsParams
     ),
     responses(
-        (status = 200, description = "Enhanced search results with snippets and suggestions", body = SearchResponse),
+        (status = 200, description = "Enhanced search results with snippets, suggestions and optionally facet counts", body = SearchResponse),
+        (status = 400, description = "Invalid search query"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     )
@@ -116,31 +166,71 @@ async fn search_documents(
 async fn enhanced_search_documents(
     State(state): State<Arc<AppState>>,
     auth_user: AuthUser,
-    Query(search_request): Query<SearchRequest>,
+    Query(mut search_request): Query<SearchRequest>,
+    Query(facets_params): Query<SearchFacetsParams>,
 ) -> Result<Json<SearchResponse>, StatusCode> {
+    if matches!(search_request.search_mode, None | Some(SearchMode::Simple)) {
+        search_query::parse(&search_request.query).map_err(|_| StatusCode::BAD_REQUEST)?;
+    }
+    geo::location_filters(search_request.near.as_deref(), search_request.bbox.as_deref())
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
     // Generate suggestions before moving search_request
     let suggestions = generate_search_suggestions(&search_request.query);
     
     let start_time = std::time::Instant::now();
-    let documents = state
-        .db
-        .enhanced_search_documents_with_role(auth_user.user.id, auth_user.user.role, &search_request)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    let query_time = start_time.elapsed().as_millis() as u64;
-    let total = documents.len() as i64;
+    let query = SearchQuery {
+        user_id: auth_user.user.id,
+        role: auth_user.user.role,
+        request: &search_request,
+        limit: search_request.limit.unwrap_or(25).clamp(1, 1000),
+        offset: search_request.offset.unwrap_or(0).max(0),
+        enhanced: true,
+    };
+    let (engine, results) = search_backend::search(&state.db, &query).await;
+    let total = results.as_ref().ok().map(|results| results.total);
+    record_search("search.enhanced", start_time, auth_user.user.id, &search_request, engine, total);
+    let results = results.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Facets count what the documents were found with
+    if results.fuzzy_fallback {
+        search_request.search_mode = Some(SearchMode::Fuzzy);
+    }
+    let facets = search_facets(&state, &auth_user, &search_request, &facets_params).await?;
 
     let response = SearchResponse {
-        documents,
-        total,
-        query_time_ms: query_time,
+        documents: results.documents,
+        total: results.total,
+        query_time_ms: start_time.elapsed().as_millis() as u64,
         suggestions,
+        facets,
     };
 
     Ok(Json(response))
 }
 
+/// Facet counts over all documents matching the search, when the client asked for them.
+/// They always come from Postgres, also when the external engine returned the results.
+async fn search_facets(
+    state: &AppState,
+    auth_user: &AuthUser,
+    search_request: &SearchRequest,
+    params: &SearchFacetsParams,
+) -> Result<Option<SearchResultFacets>, StatusCode> {
+    if !params.include_facets.unwrap_or(false) {
+        return Ok(None);
+    }
+    let facets = state
+        .db
+        .search_result_facets(auth_user.user.id, auth_user.user.role, search_request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to compute search facets: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Some(facets))
+}
+
 fn generate_search_suggestions(query: &str) -> Vec<String> {
     // Simple suggestion generation - could be enhanced with a proper suggestion system
     let mut suggestions = Vec::new();
@@ -205,4 +295,444 @@ async fn get_search_facets(
     };
 
     Ok(Json(response))
-}
\ No newline at end of file
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/search/reindex",
+    tag = "search",
+    description = "Rebuild the full-text index in the background, of every document or of those indexed in one language (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(SearchReindexQuery),
+    responses(
+        (status = 202, description = "Rebuild started; poll it for progress", body = SearchIndexRebuild),
+        (status = 400, description = "Unknown search language"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 409, description = "A rebuild is already running"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn reindex_search(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<SearchReindexQuery>,
+) -> Result<(StatusCode, Json<SearchIndexRebuild>), StatusCode> {
+    require_admin(&auth_user)?;
+    if query.language.as_deref().is_some_and(|language| !SEARCH_LANGUAGES.contains(&language)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let rebuild = SearchIndexService::new(state.db.clone())
+        .start_rebuild(query.language.as_deref(), auth_user.user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to start search index rebuild: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    Ok((StatusCode::ACCEPTED, Json(rebuild)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/search/reindex",
+    tag = "search",
+    description = "The most recent full-text index rebuilds with their progress, newest first (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Recent rebuilds", body = Vec<SearchIndexRebuild>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_reindex_runs(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<SearchIndexRebuild>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let rebuilds = state.db.list_search_index_rebuilds(20).await.map_err(|e| {
+        tracing::error!("Failed to list search index rebuilds: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(rebuilds))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/search/reindex/{id}",
+    tag = "search",
+    description = "Progress of a full-text index rebuild (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Rebuild ID")
+    ),
+    responses(
+        (status = 200, description = "Rebuild with documents processed out of the total", body = SearchIndexRebuild),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Rebuild not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_reindex_run(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(rebuild_id): Path<Uuid>,
+) -> Result<Json<SearchIndexRebuild>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let rebuild = state
+        .db
+        .get_search_index_rebuild(rebuild_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get search index rebuild {}: {}", rebuild_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(rebuild))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/search/analyzers",
+    tag = "search",
+    description = "How each search language is analyzed: accent folding and the dictionaries words are looked up in (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Analyzer of every search language", body = Vec<SearchAnalyzer>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_search_analyzers(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<SearchAnalyzer>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let analyzers = SearchIndexService::new(state.db.clone()).analyzers().await.map_err(|e| {
+        tracing::error!("Failed to list search analyzers: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(analyzers))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/search/analyzers/{language}",
+    tag = "search",
+    description = "Change how a search language is analyzed and, unless `rebuild` is false, rebuild the index of the documents indexed in it (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("language" = String, Path, description = "Search language, e.g. `german` or `simple`")
+    ),
+    request_body = UpdateSearchAnalyzerRequest,
+    responses(
+        (status = 200, description = "Analyzer applied; `rebuild` is the rebuild started, if any", body = SearchAnalyzerUpdateResponse),
+        (status = 400, description = "Unknown language, or invalid or uninstalled dictionaries"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 409, description = "Analyzer applied, but another rebuild is running; rebuild once it is done"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn update_search_analyzer(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(language): Path<String>,
+    Json(request): Json<UpdateSearchAnalyzerRequest>,
+) -> Result<Json<SearchAnalyzerUpdateResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    if !SEARCH_LANGUAGES.contains(&language.as_str()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(reason) = request.validate() {
+        tracing::debug!("Rejected search analyzer for {}: {}", language, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let internal_error = |e: anyhow::Error| {
+        tracing::error!("Failed to update the search analyzer for {}: {}", language, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let missing = state.db.missing_search_dictionaries(&request.dictionaries).await.map_err(internal_error)?;
+    if !missing.is_empty() {
+        tracing::debug!("Rejected search analyzer for {}: dictionaries not installed: {:?}", language, missing);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let analyzer = state
+        .db
+        .apply_search_analyzer(&language, request.fold_accents, &request.dictionaries, auth_user.user.id)
+        .await
+        .map_err(internal_error)?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SETTINGS_UPDATE, "search_analyzer", None).after(serde_json::json!({
+            "language": analyzer.language,
+            "fold_accents": analyzer.fold_accents,
+            "dictionaries": analyzer.dictionaries,
+        })),
+    )
+    .await;
+
+    if !request.rebuild {
+        return Ok(Json(SearchAnalyzerUpdateResponse { analyzer, rebuild: None }));
+    }
+    let rebuild = SearchIndexService::new(state.db.clone())
+        .start_rebuild(Some(&language), auth_user.user.id)
+        .await
+        .map_err(internal_error)?
+        .ok_or(StatusCode::CONFLICT)?;
+    Ok(Json(SearchAnalyzerUpdateResponse { analyzer, rebuild: Some(rebuild) }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/search/synonyms",
+    tag = "search",
+    description = "Synonyms searches are expanded with (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(SearchSynonymsQuery),
+    responses(
+        (status = 200, description = "Synonyms by language and term", body = Vec<SearchSynonym>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_search_synonyms(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<SearchSynonymsQuery>,
+) -> Result<Json<Vec<SearchSynonym>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let synonyms = state.db.list_search_synonyms(query.language.as_deref()).await.map_err(|e| {
+        tracing::error!("Failed to list search synonyms: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(synonyms))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/search/synonyms",
+    tag = "search",
+    description = "Set the synonyms of a term: searches for the term in that language also find them. Takes effect immediately, without a rebuild (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateSearchSynonymRequest,
+    responses(
+        (status = 200, description = "The term's synonyms", body = SearchSynonym),
+        (status = 400, description = "Unknown language, no term or no synonyms"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn set_search_synonym(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<CreateSearchSynonymRequest>,
+) -> Result<Json<SearchSynonym>, StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        tracing::debug!("Rejected search synonym: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let synonyms: Vec<String> = request
+        .synonyms
+        .iter()
+        .map(|synonym| synonym.trim().to_string())
+        .filter(|synonym| !synonym.is_empty())
+        .collect();
+    let synonym = state
+        .db
+        .upsert_search_synonym(&request.language, request.term.trim(), &synonyms, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save search synonym: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SETTINGS_UPDATE, "search_synonym", Some(synonym.id)).after(serde_json::json!({
+            "language": synonym.language,
+            "term": synonym.term,
+            "synonyms": synonym.synonyms,
+        })),
+    )
+    .await;
+
+    Ok(Json(synonym))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/search/synonyms/{id}",
+    tag = "search",
+    description = "Stop expanding searches for a term with its synonyms (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Synonym entry ID")
+    ),
+    responses(
+        (status = 204, description = "Synonyms removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Synonym entry not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn delete_search_synonym(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(synonym_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let deleted = state.db.delete_search_synonym(synonym_id).await.map_err(|e| {
+        tracing::error!("Failed to delete search synonym {}: {}", synonym_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SETTINGS_UPDATE, "search_synonym", Some(synonym_id))
+            .details(serde_json::json!({ "deleted": true })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/search/external/status",
+    tag = "search",
+    description = "External search engine configuration and indexing progress (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "External search status", body = ExternalSearchStatus),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_external_search_status(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<ExternalSearchStatus>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let Some(search) = external_search::configured() else {
+        return Ok(Json(ExternalSearchStatus {
+            enabled: false,
+            engine: None,
+            index: None,
+            query_enabled: false,
+            fields: Vec::new(),
+            state: None,
+        }));
+    };
+
+    let config = &search.config;
+    let index_state = state
+        .db
+        .get_external_search_state(config.engine.as_str(), &config.index)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(ExternalSearchStatus {
+        enabled: true,
+        engine: Some(config.engine.as_str().to_string()),
+        index: Some(config.index.clone()),
+        query_enabled: config.query_enabled,
+        fields: config
+            .mapping
+            .fields
+            .iter()
+            .map(|mapped| format!("{}:{}", mapped.field.as_str(), mapped.name))
+            .collect(),
+        state: index_state,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/search/external/resync",
+    tag = "search",
+    description = "Send every document to the external search engine again in the background (admin only)",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 202, description = "Re-sync started"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "No external search engine is configured"),
+        (status = 409, description = "A re-sync is already running"),
+        (status = 502, description = "The search engine could not be reached")
+    )
+)]
+async fn resync_external_search(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let search = external_search::configured().ok_or(StatusCode::NOT_FOUND)?;
+    // The index may have been dropped to apply a new mapping
+    search.client.ensure_index().await.map_err(|e| {
+        tracing::error!("External search index is unavailable: {}", e);
+        StatusCode::BAD_GATEWAY
+    })?;
+
+    match ExternalSearchIndexer::new(state.db.clone(), search).start_resync().await {
+        Ok(true) => Ok(StatusCode::ACCEPTED),
+        Ok(false) => Err(StatusCode::CONFLICT),
+        Err(e) => {
+            tracing::error!("Failed to start external search re-sync: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
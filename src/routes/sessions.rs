@@ -0,0 +1,315 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::{session_id_from_headers, start_impersonation, AuthUser},
+    models::{ImpersonateRequest, ImpersonationResponse, UserRole, UserSession},
+    routes::queue::require_admin,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    AppState,
+};
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// List the current user's active sessions
+#[utoipa::path(
+    get,
+    path = "/api/users/me/sessions",
+    tag = "sessions",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Active sessions, most recently used first", body = Vec<UserSession>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_sessions(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    headers: HeaderMap,
+) -> Result<Json<Vec<UserSession>>, StatusCode> {
+    let current = session_id_from_headers(&headers, &state.config.jwt_secret);
+    let mut sessions = state
+        .db
+        .get_user_sessions(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list sessions", e))?;
+    for session in &mut sessions {
+        session.current = Some(session.id) == current;
+    }
+
+    Ok(Json(sessions))
+}
+
+/// End one of the current user's sessions, logging that device out
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/sessions/{id}",
+    tag = "sessions",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Session ID")
+    ),
+    responses(
+        (status = 204, description = "Session ended"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Session not found or already ended"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn revoke_session(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(session_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let revoked = state
+        .db
+        .revoke_user_session(session_id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to revoke session", e))?;
+    if !revoked {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SESSION_REVOKE, "user", Some(auth_user.user.id))
+            .details(json!({ "session_id": session_id })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// End every session of the current user except the one making the request
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/sessions",
+    tag = "sessions",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Number of sessions ended", body = serde_json::Value),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn revoke_other_sessions(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let current = session_id_from_headers(&headers, &state.config.jwt_secret);
+    let revoked = state
+        .db
+        .revoke_user_sessions(auth_user.user.id, current)
+        .await
+        .map_err(|e| internal_error("Failed to revoke sessions", e))?;
+
+    if revoked > 0 {
+        audit_service::record(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::SESSION_REVOKE, "user", Some(auth_user.user.id))
+                .details(json!({ "all_other_sessions": true, "revoked": revoked })),
+        )
+        .await;
+    }
+
+    Ok(Json(json!({ "revoked": revoked })))
+}
+
+/// End the session making the request
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Logged out"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn logout(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    // API tokens and tokens from before sessions were tracked have nothing to end
+    if let Some(session_id) = session_id_from_headers(&headers, &state.config.jwt_secret) {
+        state
+            .db
+            .revoke_user_session(session_id, auth_user.user.id)
+            .await
+            .map_err(|e| internal_error("Failed to revoke session", e))?;
+
+        audit_service::record(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::LOGOUT, "user", Some(auth_user.user.id))
+                .details(json!({ "session_id": session_id })),
+        )
+        .await;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// End every session of a user, logging them out everywhere (admin only)
+///
+/// API tokens are not affected; revoke those separately.
+#[utoipa::path(
+    delete,
+    path = "/api/users/{id}/sessions",
+    tag = "sessions",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Number of sessions ended", body = serde_json::Value),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn revoke_user_sessions(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let revoked = state
+        .db
+        .revoke_user_sessions(user_id, None)
+        .await
+        .map_err(|e| internal_error("Failed to revoke sessions", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SESSION_REVOKE, "user", Some(user_id))
+            .details(json!({ "all_sessions": true, "revoked": revoked, "by_admin": true })),
+    )
+    .await;
+    info!("Admin {} logged out user {} everywhere ({} sessions)", auth_user.user.id, user_id, revoked);
+
+    Ok(Json(json!({ "revoked": revoked })))
+}
+
+/// Act as a user for a while, to reproduce what they can and cannot see without
+/// asking for their password (admin only)
+///
+/// The returned token is bound to a session of the user that ends when it expires,
+/// when it is logged out, or when the user's sessions are revoked; it stops working
+/// as soon as the caller is no longer an admin. Tokens are read-only unless
+/// `read_only` is false, and never change the user's account or credentials.
+/// Everything done with the token is recorded in the audit log under the user with
+/// `impersonator_id` and `impersonator_username` set to the admin.
+#[utoipa::path(
+    post,
+    path = "/api/users/{id}/impersonate",
+    tag = "sessions",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    request_body = ImpersonateRequest,
+    responses(
+        (status = 200, description = "Token acting as the user", body = ImpersonationResponse),
+        (status = 400, description = "Missing reason or invalid lifetime"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required, or already impersonating"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "Admins and yourself cannot be impersonated"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn impersonate_user(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(user_id): Path<Uuid>,
+    Json(request): Json<ImpersonateRequest>,
+) -> Result<Json<ImpersonationResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    if client.impersonator.is_some() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let minutes = request.validate().map_err(|e| {
+        warn!("Rejected impersonation of user {}: {}", user_id, e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let read_only = request.read_only.unwrap_or(true);
+
+    let user = state
+        .db
+        .get_user_by_id(user_id)
+        .await
+        .map_err(|e| internal_error("Failed to get user", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    // Acting as another admin would hand out their admin rights under a different name
+    if user.id == auth_user.user.id || user.role == UserRole::Admin {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let (token, session) = start_impersonation(&state, &auth_user.user, &user, minutes, read_only, &client)
+        .await
+        .map_err(|e| internal_error("Failed to start impersonation", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::USER_IMPERSONATE, "user", Some(user.id)).details(json!({
+            "username": user.username,
+            "reason": request.reason.trim(),
+            "minutes": minutes,
+            "read_only": read_only,
+            "session_id": session.id,
+        })),
+    )
+    .await;
+    info!("Admin {} is impersonating user {} for {} minutes", auth_user.user.id, user.id, minutes);
+
+    Ok(Json(ImpersonationResponse {
+        token,
+        user_id: user.id,
+        username: user.username,
+        session_id: session.id,
+        expires_at: session.expires_at,
+        read_only,
+    }))
+}
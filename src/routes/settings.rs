@@ -1,16 +1,21 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::Json,
     routing::get,
     Router,
 };
 use std::sync::Arc;
+use tracing::{debug, error};
 
 use crate::{
     auth::AuthUser,
     errors::settings::SettingsError,
-    models::{SettingsResponse, UpdateSettings, UserRole},
+    models::{
+        validate_preference_namespace, ClientPreference, Settings, SettingsResponse, UpdateClientPreferenceRequest,
+        UpdateSettings, UserRole, MAX_CLIENT_PREFERENCE_NAMESPACES,
+    },
+    services::audit_service::{self, AuditEvent, ClientInfo},
     AppState,
 };
 use serde::Serialize;
@@ -19,6 +24,11 @@ pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(get_settings).put(update_settings))
         .route("/config", get(get_server_configuration))
+        .route("/preferences", get(list_client_preferences))
+        .route(
+            "/preferences/{namespace}",
+            get(get_client_preference).put(update_client_preference).delete(delete_client_preference),
+        )
 }
 
 #[utoipa::path(
@@ -129,14 +139,53 @@ async fn get_settings(
 async fn update_settings(
     auth_user: AuthUser,
     State(state): State<Arc<AppState>>,
+    client: ClientInfo,
     Json(update_data): Json<UpdateSettings>,
 ) -> Result<Json<SettingsResponse>, StatusCode> {
+    let previous = state
+        .db
+        .get_user_settings(auth_user.user.id)
+        .await
+        .ok()
+        .flatten();
+
     let settings = state
         .db
         .create_or_update_settings(auth_user.user.id, &update_data)
         .await
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
+    // Users without a settings row were running on the defaults
+    let as_response = |settings: Settings| serde_json::to_value(SettingsResponse::from(settings)).unwrap_or_default();
+    let before = as_response(previous.clone().unwrap_or_default());
+    let after = as_response(settings.clone());
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SETTINGS_UPDATE, "settings", Some(settings.id)).changes(&before, &after),
+    )
+    .await;
+
+    // Documents are indexed with the language rules of their owner's OCR language
+    let language_changed = match previous {
+        Some(previous) => {
+            previous.primary_language != settings.primary_language
+                || previous.ocr_language != settings.ocr_language
+        }
+        None => true,
+    };
+    if language_changed {
+        let db = state.db.clone();
+        let user_id = auth_user.user.id;
+        crate::errors::panic::spawn_guarded(format!("Search reindex for user {}", user_id), async move {
+            match db.rebuild_search_vectors(Some(user_id), 500).await {
+                Ok(count) => tracing::info!("Reindexed {} documents for user {} after a language change", count, user_id),
+                Err(e) => tracing::error!("Failed to reindex documents for user {}: {}", user_id, e),
+            }
+        });
+    }
+
     Ok(Json(settings.into()))
 }
 
@@ -222,4 +271,175 @@ async fn get_server_configuration(
     };
 
     Ok(Json(server_config))
-}
\ No newline at end of file
+}
+
+fn checked_namespace(namespace: &str) -> Result<(), StatusCode> {
+    validate_preference_namespace(namespace).map_err(|reason| {
+        debug!("Rejected preference namespace {:?}: {}", namespace, reason);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+/// All client preferences of the current user
+#[utoipa::path(
+    get,
+    path = "/api/settings/preferences",
+    tag = "settings",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Preferences by namespace", body = Vec<ClientPreference>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_client_preferences(
+    auth_user: AuthUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ClientPreference>>, StatusCode> {
+    let preferences = state.db.list_client_preferences(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to list client preferences: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(preferences))
+}
+
+/// The current user's preferences of one namespace
+#[utoipa::path(
+    get,
+    path = "/api/settings/preferences/{namespace}",
+    tag = "settings",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("namespace" = String, Path, description = "e.g. web.document-list")
+    ),
+    responses(
+        (status = 200, description = "Stored preferences", body = ClientPreference),
+        (status = 400, description = "Invalid namespace"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Nothing stored under the namespace"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_client_preference(
+    auth_user: AuthUser,
+    State(state): State<Arc<AppState>>,
+    Path(namespace): Path<String>,
+) -> Result<Json<ClientPreference>, StatusCode> {
+    checked_namespace(&namespace)?;
+    let preference = state
+        .db
+        .get_client_preference(auth_user.user.id, &namespace)
+        .await
+        .map_err(|e| {
+            error!("Failed to get client preference {}: {}", namespace, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(preference))
+}
+
+/// Replace the current user's preferences of one namespace
+///
+/// Values are at most 64 KiB of JSON and a user keeps at most 100 namespaces.
+#[utoipa::path(
+    put,
+    path = "/api/settings/preferences/{namespace}",
+    tag = "settings",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("namespace" = String, Path, description = "e.g. web.document-list")
+    ),
+    request_body = UpdateClientPreferenceRequest,
+    responses(
+        (status = 200, description = "Preferences stored", body = ClientPreference),
+        (status = 400, description = "Invalid namespace or schema version, or too many namespaces"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "A higher schema version is stored"),
+        (status = 413, description = "Value too large"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn update_client_preference(
+    auth_user: AuthUser,
+    State(state): State<Arc<AppState>>,
+    Path(namespace): Path<String>,
+    Json(request): Json<UpdateClientPreferenceRequest>,
+) -> Result<Json<ClientPreference>, StatusCode> {
+    checked_namespace(&namespace)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected client preference {}: {}", namespace, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if !request.fits() {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let internal_error = |e: anyhow::Error| {
+        error!("Failed to store client preference {}: {}", namespace, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let user_id = auth_user.user.id;
+    let exists = state.db.get_client_preference(user_id, &namespace).await.map_err(internal_error)?.is_some();
+    if !exists && state.db.count_client_preferences(user_id).await.map_err(internal_error)? >= MAX_CLIENT_PREFERENCE_NAMESPACES {
+        debug!("User {} keeps the maximum number of preference namespaces", user_id);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let preference = state
+        .db
+        .upsert_client_preference(user_id, &namespace, request.schema_version(), &request.value)
+        .await
+        .map_err(internal_error)?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    Ok(Json(preference))
+}
+
+/// Remove the current user's preferences of one namespace
+#[utoipa::path(
+    delete,
+    path = "/api/settings/preferences/{namespace}",
+    tag = "settings",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("namespace" = String, Path, description = "e.g. web.document-list")
+    ),
+    responses(
+        (status = 204, description = "Preferences removed"),
+        (status = 400, description = "Invalid namespace"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Nothing stored under the namespace"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn delete_client_preference(
+    auth_user: AuthUser,
+    State(state): State<Arc<AppState>>,
+    Path(namespace): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    checked_namespace(&namespace)?;
+    let deleted = state
+        .db
+        .delete_client_preference(auth_user.user.id, &namespace)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete client preference {}: {}", namespace, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
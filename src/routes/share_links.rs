@@ -0,0 +1,526 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Json, Response},
+    routing::{delete, get},
+    Router,
+};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    db::share_links::NewShareLink,
+    embedded_metadata,
+    models::{
+        CreateShareLinkRequest, CreateShareLinkResponse, Document, PublicShareLinkInfo, ShareLink,
+        ShareLinkPasswordRequest, ShareLinksQuery, UserRole,
+    },
+    routes::queue::require_admin,
+    services::{
+        audit_service::{self, document_details, AuditEvent, ClientInfo},
+        share_link_service,
+    },
+    AppState,
+};
+
+/// Header carrying the password of a protected link on GET requests
+pub const SHARE_PASSWORD_HEADER: &str = "x-share-password";
+/// Longest allowed lifetime of a link
+const MAX_EXPIRY_HOURS: i64 = 24 * 365;
+/// bcrypt only looks at the first 72 bytes
+const MAX_PASSWORD_BYTES: usize = 72;
+
+/// Listing and revoking links, for admins and document owners
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_share_links))
+        .route("/{id}", delete(revoke_share_link))
+}
+
+/// Opening links, without an account
+pub fn public_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/{token}", get(get_public_share))
+        .route("/{token}/download", get(download_public_share).post(download_public_share_with_password))
+}
+
+fn default_watermark_text(link: &ShareLink) -> String {
+    format!(
+        "Shared via Readur on {} - link {}",
+        link.created_at.format("%Y-%m-%d"),
+        &link.id.simple().to_string()[..8]
+    )
+}
+
+/// A document the user may create links for: their own, or any for admins
+async fn linkable_document(state: &AppState, auth_user: &AuthUser, document_id: Uuid) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// The active link for a token, once its password, if any, is given. Unknown tokens are
+/// 404, links that were revoked, expired or used up 410.
+async fn open_link(state: &AppState, token: &str, password: Option<&str>) -> Result<ShareLink, StatusCode> {
+    let link = find_active_link(state, token).await?;
+
+    if let Some(password_hash) = &link.password_hash {
+        let password = password.ok_or(StatusCode::UNAUTHORIZED)?;
+        if !bcrypt::verify(password, password_hash).unwrap_or(false) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    Ok(link)
+}
+
+async fn find_active_link(state: &AppState, token: &str) -> Result<ShareLink, StatusCode> {
+    let link = state
+        .db
+        .get_share_link_by_token_hash(&share_link_service::hash_token(token))
+        .await
+        .map_err(|e| {
+            error!("Failed to look up share link: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !link.is_active(Utc::now()) {
+        return Err(StatusCode::GONE);
+    }
+
+    // An embargoed document is not there for anyone but admins until it is released
+    let embargoed = state.db.is_embargoed(link.document_id).await.map_err(|e| {
+        error!("Failed to check the embargo of document {}: {}", link.document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if embargoed {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(link)
+}
+
+async fn shared_document(state: &AppState, link: &ShareLink) -> Result<Document, StatusCode> {
+    state
+        .db
+        .get_document_by_id(link.document_id, Uuid::nil(), UserRole::Admin)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up document {} of share link {}: {}", link.document_id, link.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Create a public link to a document
+///
+/// The response holds the only copy of the token; the server keeps just its hash.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{id}/share",
+    tag = "share_links",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    request_body = CreateShareLinkRequest,
+    responses(
+        (status = 201, description = "Link created", body = CreateShareLinkResponse),
+        (status = 400, description = "Invalid expiry, download limit, password or watermark, or watermarking a document that is not a PDF"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_share_link(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(document_id): Path<Uuid>,
+    Json(request): Json<CreateShareLinkRequest>,
+) -> Result<(StatusCode, Json<CreateShareLinkResponse>), StatusCode> {
+    let document = linkable_document(&state, &auth_user, document_id).await?;
+
+    if request.expires_in_hours.is_some_and(|hours| !(1..=MAX_EXPIRY_HOURS).contains(&hours)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if request.max_downloads.is_some_and(|max| max < 1) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let password = request.password.as_deref().filter(|p| !p.is_empty());
+    if password.is_some_and(|p| p.len() > MAX_PASSWORD_BYTES) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let watermark = request.watermark.unwrap_or(false);
+    if watermark && !share_link_service::can_watermark(&document.mime_type) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let watermark_text = request
+        .watermark_text
+        .as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty());
+    if watermark_text.is_some_and(|text| text.chars().count() > share_link_service::MAX_WATERMARK_LENGTH) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let password_hash = password
+        .map(|p| bcrypt::hash(p, 12))
+        .transpose()
+        .map_err(|e| {
+            error!("Failed to hash share link password: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let token = share_link_service::generate_token();
+    let token_hash = share_link_service::hash_token(&token);
+    let link = state
+        .db
+        .create_share_link(&NewShareLink {
+            document_id,
+            created_by: auth_user.user.id,
+            token_hash: &token_hash,
+            password_hash: password_hash.as_deref(),
+            expires_at: request.expires_in_hours.map(|hours| Utc::now() + Duration::hours(hours)),
+            max_downloads: request.max_downloads,
+            watermark,
+            watermark_text,
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to create share link for document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SHARE_LINK_CREATE, "document", Some(document_id)).details(json!({
+            "share_link_id": link.id,
+            "expires_at": link.expires_at,
+            "max_downloads": link.max_downloads,
+            "has_password": link.has_password,
+            "watermark": link.watermark,
+        })),
+    )
+    .await;
+
+    info!("User {} created share link {} for document {}", auth_user.user.id, link.id, document_id);
+    let url = format!("/api/public/shares/{}", token);
+    Ok((StatusCode::CREATED, Json(CreateShareLinkResponse { link, token, url })))
+}
+
+/// List the public links of a document, including revoked and expired ones
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/share",
+    tag = "share_links",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Links of the document, newest first", body = Vec<ShareLink>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_document_share_links(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(document_id): Path<Uuid>,
+) -> Result<Json<Vec<ShareLink>>, StatusCode> {
+    linkable_document(&state, &auth_user, document_id).await?;
+
+    let links = state.db.get_document_share_links(document_id).await.map_err(|e| {
+        error!("Failed to list share links of document {}: {}", document_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(links))
+}
+
+/// List the public links of all users (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/share-links",
+    tag = "share_links",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(ShareLinksQuery),
+    responses(
+        (status = 200, description = "Links, newest first; only usable ones unless include_inactive", body = Vec<ShareLink>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_share_links(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<ShareLinksQuery>,
+) -> Result<Json<Vec<ShareLink>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let links = state
+        .db
+        .get_share_links(query.include_inactive.unwrap_or(false), limit, offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to list share links: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(links))
+}
+
+/// Revoke a public link; the document's owner or an admin
+#[utoipa::path(
+    delete,
+    path = "/api/share-links/{id}",
+    tag = "share_links",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Share link ID")
+    ),
+    responses(
+        (status = 204, description = "Link revoked"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Link not found or already revoked"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn revoke_share_link(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let link = state
+        .db
+        .get_share_link(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up share link {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    linkable_document(&state, &auth_user, link.document_id).await?;
+
+    let revoked = state.db.revoke_share_link(id, auth_user.user.id).await.map_err(|e| {
+        error!("Failed to revoke share link {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !revoked {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SHARE_LINK_REVOKE, "document", Some(link.document_id))
+            .details(json!({ "share_link_id": id, "download_count": link.download_count })),
+    )
+    .await;
+
+    info!("User {} revoked share link {}", auth_user.user.id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// What a public link points to
+///
+/// File details are left out of password protected links until the password is sent
+/// in the `X-Share-Password` header.
+#[utoipa::path(
+    get,
+    path = "/api/public/shares/{token}",
+    tag = "share_links",
+    params(
+        ("token" = String, Path, description = "Link token")
+    ),
+    responses(
+        (status = 200, description = "Link details", body = PublicShareLinkInfo),
+        (status = 404, description = "Unknown link"),
+        (status = 410, description = "Link revoked, expired or used up"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_public_share(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<PublicShareLinkInfo>, StatusCode> {
+    let link = find_active_link(&state, &token).await?;
+    let unlocked = match &link.password_hash {
+        None => true,
+        Some(password_hash) => share_password(&headers)
+            .is_some_and(|password| bcrypt::verify(password, password_hash).unwrap_or(false)),
+    };
+
+    let document = if unlocked { Some(shared_document(&state, &link).await?) } else { None };
+
+    Ok(Json(PublicShareLinkInfo {
+        filename: document.as_ref().map(|d| d.original_filename.clone()),
+        mime_type: document.as_ref().map(|d| d.mime_type.clone()),
+        file_size: document.as_ref().map(|d| d.file_size),
+        requires_password: link.has_password,
+        expires_at: link.expires_at,
+        downloads_remaining: link.max_downloads.map(|max| max - link.download_count),
+        watermarked: link.watermark,
+    }))
+}
+
+/// Download the document behind a public link
+///
+/// Protected links take the password in the `X-Share-Password` header.
+#[utoipa::path(
+    get,
+    path = "/api/public/shares/{token}/download",
+    tag = "share_links",
+    params(
+        ("token" = String, Path, description = "Link token")
+    ),
+    responses(
+        (status = 200, description = "The document, watermarked if the link asks for it", content_type = "application/octet-stream"),
+        (status = 401, description = "Password missing or wrong"),
+        (status = 404, description = "Unknown link"),
+        (status = 410, description = "Link revoked, expired or used up"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn download_public_share(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    serve_share_link(&state, &client, &token, share_password(&headers)).await
+}
+
+/// Download the document behind a password protected link, for HTML forms
+#[utoipa::path(
+    post,
+    path = "/api/public/shares/{token}/download",
+    tag = "share_links",
+    params(
+        ("token" = String, Path, description = "Link token")
+    ),
+    request_body = ShareLinkPasswordRequest,
+    responses(
+        (status = 200, description = "The document, watermarked if the link asks for it", content_type = "application/octet-stream"),
+        (status = 401, description = "Wrong password"),
+        (status = 404, description = "Unknown link"),
+        (status = 410, description = "Link revoked, expired or used up"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn download_public_share_with_password(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Path(token): Path<String>,
+    Json(request): Json<ShareLinkPasswordRequest>,
+) -> Result<Response<Body>, StatusCode> {
+    serve_share_link(&state, &client, &token, Some(&request.password)).await
+}
+
+fn share_password(headers: &HeaderMap) -> Option<&str> {
+    headers.get(SHARE_PASSWORD_HEADER).and_then(|v| v.to_str().ok())
+}
+
+async fn serve_share_link(
+    state: &AppState,
+    client: &ClientInfo,
+    token: &str,
+    password: Option<&str>,
+) -> Result<Response<Body>, StatusCode> {
+    let link = open_link(state, token, password).await?;
+    let document = shared_document(state, &link).await?;
+
+    let mut file_data = state.file_service.read_file(&document.file_path).await.map_err(|e| {
+        error!("Failed to read document file {}: {}", document.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if link.watermark {
+        // A new version may have replaced the PDF since the link was created
+        if !share_link_service::can_watermark(&document.mime_type) {
+            warn!("Share link {} asks for a watermark but document {} is no longer a PDF", link.id, document.id);
+            return Err(StatusCode::CONFLICT);
+        }
+        let text = link.watermark_text.clone().unwrap_or_else(|| default_watermark_text(&link));
+        file_data = share_link_service::watermark_pdf(&file_data, &text).await.map_err(|e| {
+            error!("Failed to watermark document {} for share link {}: {}", document.id, link.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    let strip_policy = embedded_metadata::configured().map_err(|e| {
+        error!("Invalid metadata stripping configuration: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let mut metadata_stripped = false;
+    if strip_policy.strips_shared() {
+        if let Some(stripped) = embedded_metadata::strip(&file_data, &document.mime_type) {
+            file_data = stripped;
+            metadata_stripped = true;
+        }
+    }
+
+    // Counted only once the file is ready, so failed downloads do not use up the link
+    let claimed = state.db.claim_share_link_download(link.id).await.map_err(|e| {
+        error!("Failed to count download of share link {}: {}", link.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !claimed {
+        return Err(StatusCode::GONE);
+    }
+
+    let mut details = document_details(&document);
+    details["share_link_id"] = json!(link.id);
+    details["watermarked"] = json!(link.watermark);
+    details["metadata_stripped"] = json!(metadata_stripped);
+    audit_service::record(
+        &state.db,
+        None,
+        client,
+        AuditEvent::new(audit_service::DOCUMENT_DOWNLOAD, "document", Some(document.id)).details(details),
+    )
+    .await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, document.mime_type)
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", document.original_filename))
+        .header(header::CONTENT_LENGTH, file_data.len().to_string())
+        .header(header::CACHE_CONTROL, "no-store")
+        .header("X-Robots-Tag", "noindex")
+        .body(Body::from(file_data))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
@@ -0,0 +1,506 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{delete, get},
+    Router,
+};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        CreateNotification, CreateShareRequest, Share, SharePermission, SharedDocument, SharedDocumentsQuery,
+        UserRole,
+    },
+    services::i18n,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/documents", get(list_shared_documents))
+        .route("/documents/{id}", get(list_document_shares).post(share_document))
+        .route("/documents/{id}/{share_id}", delete(unshare_document))
+        .route("/labels/{id}", get(list_label_shares).post(share_label))
+        .route("/labels/{id}/{share_id}", delete(unshare_label))
+        .route("/collections/{id}", get(list_collection_shares).post(share_collection))
+        .route("/collections/{id}/{share_id}", delete(unshare_collection))
+}
+
+/// Owner of a document the user may manage the shares of: their own, or any for admins
+async fn shareable_document_owner(state: &AppState, auth_user: &AuthUser, document_id: Uuid) -> Result<Uuid, StatusCode> {
+    let document = state
+        .db
+        .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up document {}: {}", document_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(document.user_id)
+}
+
+/// Owner of a label the user may manage the shares of: their own, or any user label
+/// for admins. System labels cannot be shared.
+async fn shareable_label_owner(state: &AppState, auth_user: &AuthUser, label_id: Uuid) -> Result<Uuid, StatusCode> {
+    let owner = state.db.get_label_owner(label_id).await.map_err(|e| {
+        error!("Failed to look up label {}: {}", label_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    owner
+        .filter(|owner| *owner == auth_user.user.id || auth_user.user.role == UserRole::Admin)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Owner of a collection the user may manage the shares of: their own, or any for admins
+async fn shareable_collection_owner(state: &AppState, auth_user: &AuthUser, collection_id: Uuid) -> Result<Uuid, StatusCode> {
+    let collection = state.db.get_collection(collection_id).await.map_err(|e| {
+        error!("Failed to look up collection {}: {}", collection_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    collection
+        .map(|collection| collection.user_id)
+        .filter(|owner| *owner == auth_user.user.id || auth_user.user.role == UserRole::Admin)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Check that a share names exactly one existing grantee other than the owner
+async fn validate_grantee(state: &AppState, request: &CreateShareRequest, owner_id: Uuid) -> Result<(), StatusCode> {
+    match (request.user_id, request.group_id) {
+        (Some(user_id), None) => {
+            if user_id == owner_id {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            state
+                .db
+                .get_user_by_id(user_id)
+                .await
+                .map_err(|e| {
+                    error!("Failed to look up user {}: {}", user_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .ok_or(StatusCode::NOT_FOUND)?;
+        }
+        (None, Some(group_id)) => {
+            state
+                .db
+                .get_user_group(group_id)
+                .await
+                .map_err(|e| {
+                    error!("Failed to look up group {}: {}", group_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .ok_or(StatusCode::NOT_FOUND)?;
+        }
+        _ => return Err(StatusCode::BAD_REQUEST),
+    }
+
+    Ok(())
+}
+
+/// Tell a user that something was shared with them, with the notification `key`;
+/// groups are not notified
+async fn notify_grantee(state: &AppState, share: &Share, key: &str, action_url: String) {
+    let Some(user_id) = share.user_id else {
+        return;
+    };
+
+    let locale = i18n::locale_of(&state.db, user_id).await;
+    let notification = CreateNotification {
+        action_url: Some(action_url),
+        metadata: Some(serde_json::json!({
+            "share_id": share.id,
+            "resource_id": share.resource_id,
+            "permission": share.permission,
+        })),
+        ..i18n::notification(&locale, "info", key, &[("permission", share.permission.to_string())])
+    };
+    if let Err(e) = state.db.create_notification(user_id, &notification).await {
+        warn!("Failed to notify user {} of share {}: {}", user_id, share.id, e);
+    }
+}
+
+/// Documents of other users shared with the current user, directly, through a group
+/// or through a shared label or collection
+#[utoipa::path(
+    get,
+    path = "/api/shares/documents",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(SharedDocumentsQuery),
+    responses(
+        (status = 200, description = "Documents shared with the current user, newest first", body = Vec<SharedDocument>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_shared_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<SharedDocumentsQuery>,
+) -> Result<Json<Vec<SharedDocument>>, StatusCode> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let documents = state
+        .db
+        .get_documents_shared_with_user(auth_user.user.id, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to list documents shared with user {}: {}", auth_user.user.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(documents))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/shares/documents/{id}",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Users and groups the document is shared with", body = Vec<Share>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_document_shares(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<Share>>, StatusCode> {
+    shareable_document_owner(&state, &auth_user, id).await?;
+
+    let shares = state.db.get_document_shares(id).await.map_err(|e| {
+        error!("Failed to list shares of document {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(shares))
+}
+
+/// Share a document with a user or a group. Only its owner and admins can share it.
+#[utoipa::path(
+    post,
+    path = "/api/shares/documents/{id}",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    request_body = CreateShareRequest,
+    responses(
+        (status = 201, description = "Document shared", body = Share),
+        (status = 400, description = "Not exactly one of user_id and group_id, or shared with the owner"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document, user or group not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn share_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CreateShareRequest>,
+) -> Result<(StatusCode, Json<Share>), StatusCode> {
+    let owner_id = shareable_document_owner(&state, &auth_user, id).await?;
+    validate_grantee(&state, &request, owner_id).await?;
+
+    let permission = request.permission.unwrap_or(SharePermission::View);
+    let share = state
+        .db
+        .share_document(id, request.user_id, request.group_id, permission, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to share document {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("User {} shared document {} with {} access (share {})", auth_user.user.id, id, permission, share.id);
+    notify_grantee(&state, &share, "notification.shared_document", format!("/documents/{}", id)).await;
+    Ok((StatusCode::CREATED, Json(share)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/shares/documents/{id}/{share_id}",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID"),
+        ("share_id" = Uuid, Path, description = "Share ID")
+    ),
+    responses(
+        (status = 204, description = "Share removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document or share not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn unshare_document(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, share_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    shareable_document_owner(&state, &auth_user, id).await?;
+
+    let deleted = state.db.delete_document_share(share_id, id).await.map_err(|e| {
+        error!("Failed to remove share {} of document {}: {}", share_id, id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/shares/labels/{id}",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Label ID")
+    ),
+    responses(
+        (status = 200, description = "Users and groups the label is shared with", body = Vec<Share>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Label not found or a system label"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_label_shares(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<Share>>, StatusCode> {
+    shareable_label_owner(&state, &auth_user, id).await?;
+
+    let shares = state.db.get_label_shares(id).await.map_err(|e| {
+        error!("Failed to list shares of label {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(shares))
+}
+
+/// Share every document of the label's owner that carries the label, including ones
+/// labelled later, with a user or a group
+#[utoipa::path(
+    post,
+    path = "/api/shares/labels/{id}",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Label ID")
+    ),
+    request_body = CreateShareRequest,
+    responses(
+        (status = 201, description = "Label shared", body = Share),
+        (status = 400, description = "Not exactly one of user_id and group_id, or shared with the owner"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Label, user or group not found, or a system label"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn share_label(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CreateShareRequest>,
+) -> Result<(StatusCode, Json<Share>), StatusCode> {
+    let owner_id = shareable_label_owner(&state, &auth_user, id).await?;
+    validate_grantee(&state, &request, owner_id).await?;
+
+    let permission = request.permission.unwrap_or(SharePermission::View);
+    let share = state
+        .db
+        .share_label(id, request.user_id, request.group_id, permission, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to share label {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("User {} shared label {} with {} access (share {})", auth_user.user.id, id, permission, share.id);
+    notify_grantee(&state, &share, "notification.shared_label", "/documents".to_string()).await;
+    Ok((StatusCode::CREATED, Json(share)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/shares/labels/{id}/{share_id}",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Label ID"),
+        ("share_id" = Uuid, Path, description = "Share ID")
+    ),
+    responses(
+        (status = 204, description = "Share removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Label or share not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn unshare_label(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, share_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    shareable_label_owner(&state, &auth_user, id).await?;
+
+    let deleted = state.db.delete_label_share(share_id, id).await.map_err(|e| {
+        error!("Failed to remove share {} of label {}: {}", share_id, id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/shares/collections/{id}",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID")
+    ),
+    responses(
+        (status = 200, description = "Users and groups the collection is shared with", body = Vec<Share>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_collection_shares(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<Share>>, StatusCode> {
+    shareable_collection_owner(&state, &auth_user, id).await?;
+
+    let shares = state.db.get_collection_shares(id).await.map_err(|e| {
+        error!("Failed to list shares of collection {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(shares))
+}
+
+/// Share a collection and its subcollections with a user or a group. The grantee gets
+/// access to every document of the collection's owner in them, including ones added
+/// later. `edit` also lets them add, remove and reorder documents and subcollections.
+#[utoipa::path(
+    post,
+    path = "/api/shares/collections/{id}",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID")
+    ),
+    request_body = CreateShareRequest,
+    responses(
+        (status = 201, description = "Collection shared", body = Share),
+        (status = 400, description = "Not exactly one of user_id and group_id, or shared with the owner"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection, user or group not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn share_collection(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CreateShareRequest>,
+) -> Result<(StatusCode, Json<Share>), StatusCode> {
+    let owner_id = shareable_collection_owner(&state, &auth_user, id).await?;
+    validate_grantee(&state, &request, owner_id).await?;
+
+    let permission = request.permission.unwrap_or(SharePermission::View);
+    let share = state
+        .db
+        .share_collection(id, request.user_id, request.group_id, permission, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to share collection {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("User {} shared collection {} with {} access (share {})", auth_user.user.id, id, permission, share.id);
+    notify_grantee(&state, &share, "notification.shared_collection", format!("/collections/{}", id)).await;
+    Ok((StatusCode::CREATED, Json(share)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/shares/collections/{id}/{share_id}",
+    tag = "shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Collection ID"),
+        ("share_id" = Uuid, Path, description = "Share ID")
+    ),
+    responses(
+        (status = 204, description = "Share removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Collection or share not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn unshare_collection(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, share_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    shareable_collection_owner(&state, &auth_user, id).await?;
+
+    let deleted = state.db.delete_collection_share(share_id, id).await.map_err(|e| {
+        error!("Failed to remove share {} of collection {}: {}", share_id, id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
@@ -1,19 +1,33 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{OriginalUri, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::Json,
 };
+use chrono::Utc;
 use std::sync::Arc;
 use uuid::Uuid;
 use tracing::{error, info};
 
 use crate::{
     auth::AuthUser,
+    db::workspaces::WorkspaceScoped,
     errors::source::SourceError,
-    models::{CreateSource, SourceResponse, SourceWithStats, UpdateSource, SourceType},
+    models::{CreateSource, Source, SourceListQuery, SourceResponse, SourceSchedule, SourceWithStats, UpdateSource, SourceType, WorkspaceRole},
+    scheduling::sync_schedule,
+    services::workspace_service,
+    utils::pagination::{Cursor, Page, PageRequest},
     AppState,
 };
 
+/// A source with its next scheduled sync and the duration of its last one
+pub(crate) fn source_response(source: Source, schedule: Option<&SourceSchedule>) -> SourceResponse {
+    let next_sync_at = sync_schedule::next_sync_at(&source, schedule, Utc::now()).ok().flatten();
+    let mut response: SourceResponse = source.into();
+    response.next_sync_at = next_sync_at;
+    response.last_sync_duration_ms = schedule.and_then(|schedule| schedule.last_sync_duration_ms);
+    response
+}
+
 /// List all sources for the authenticated user
 #[utoipa::path(
     get,
@@ -22,8 +36,10 @@ use crate::{
     security(
         ("bearer_auth" = [])
     ),
+    params(SourceListQuery),
     responses(
-        (status = 200, description = "List of user sources", body = Vec<SourceResponse>),
+        (status = 200, description = "User sources, newest first, with a `Link` to the next page when paged and `X-Total-Count`", body = Vec<SourceResponse>),
+        (status = 400, description = "Invalid cursor"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     )
@@ -31,12 +47,41 @@ use crate::{
 pub async fn list_sources(
     auth_user: AuthUser,
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<SourceResponse>>, SourceError> {
-    let sources = state
-        .db
-        .get_sources(auth_user.user.id)
-        .await
-        .map_err(|e| SourceError::connection_failed(format!("Failed to retrieve sources: {}", e)))?;
+    Query(query): Query<SourceListQuery>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<(HeaderMap, Json<Vec<SourceResponse>>), SourceError> {
+    let page = PageRequest::parse_optional(query.limit, 50, 200, query.cursor.as_deref(), query.include_total)
+        .map_err(SourceError::validation_failed)?;
+    let page = match page {
+        Some(page) => {
+            let sources = state
+                .db
+                .get_sources_page(auth_user.user.id, &page)
+                .await
+                .map_err(|e| SourceError::connection_failed(format!("Failed to retrieve sources: {}", e)))?;
+            let total = if page.include_total {
+                Some(
+                    state
+                        .db
+                        .count_sources(auth_user.user.id)
+                        .await
+                        .map_err(|e| SourceError::connection_failed(format!("Failed to count sources: {}", e)))?,
+                )
+            } else {
+                None
+            };
+            Page::new(sources, page.limit, total, |source| Cursor::new(source.created_at, source.id))
+        }
+        None => Page::whole(
+            state
+                .db
+                .get_sources(auth_user.user.id)
+                .await
+                .map_err(|e| SourceError::connection_failed(format!("Failed to retrieve sources: {}", e)))?,
+        ),
+    };
+    let headers = page.headers(&uri);
+    let sources = page.items;
 
     // Get source IDs for batch counting
     let source_ids: Vec<Uuid> = sources.iter().map(|s| s.id).collect();
@@ -54,18 +99,28 @@ pub async fn list_sources(
         .map(|(id, total, ocr)| (id, (total, ocr)))
         .collect();
 
+    let schedules: std::collections::HashMap<Uuid, SourceSchedule> = state
+        .db
+        .get_source_schedules(&source_ids)
+        .await
+        .map_err(|e| SourceError::connection_failed(format!("Failed to retrieve source schedules: {}", e)))?
+        .into_iter()
+        .map(|schedule| (schedule.source_id, schedule))
+        .collect();
+
     let responses: Vec<SourceResponse> = sources
         .into_iter()
         .map(|s| {
             let (total_docs, total_ocr) = count_map.get(&s.id).copied().unwrap_or((0, 0));
-            let mut response: SourceResponse = s.into();
+            let schedule = schedules.get(&s.id);
+            let mut response = source_response(s, schedule);
             response.total_documents = total_docs;
             response.total_documents_ocr = total_ocr;
             response
         })
         .collect();
     
-    Ok(Json(responses))
+    Ok((headers, Json(responses)))
 }
 
 /// Create a new source
@@ -81,6 +136,7 @@ pub async fn list_sources(
         (status = 201, description = "Source created successfully", body = SourceResponse),
         (status = 400, description = "Bad request - invalid source data"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "The workspace named by X-Workspace-Id requires the editor role to create sources"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -96,9 +152,21 @@ pub async fn create_source(
         return Err(SourceError::configuration_invalid(validation_error));
     }
 
-    let source = state
-        .db
-        .create_source(auth_user.user.id, &source_data)
+    // Sources import into the workspace they are created in, which takes the editor role
+    if let Some(workspace_id) = auth_user.workspace_id {
+        let allowed = workspace_service::has_role(&state.db, &auth_user.user, workspace_id, WorkspaceRole::Editor)
+            .await
+            .map_err(|e| SourceError::connection_failed(format!("Failed to look up workspace role: {}", e)))?;
+        if !allowed {
+            return Err(SourceError::access_denied(
+                workspace_id.to_string(),
+                "creating sources in this workspace requires the editor role".to_string(),
+            ));
+        }
+    }
+
+    // Credentials are sealed with the workspace's key before the source is placed in it
+    let source = workspace_service::within(auth_user.workspace_id, state.db.create_source(auth_user.user.id, &source_data))
         .await
         .map_err(|e| {
             error!("Failed to create source in database: {}", e);
@@ -110,7 +178,9 @@ pub async fn create_source(
             }
         })?;
 
-    let mut response: SourceResponse = source.into();
+    workspace_service::place_in_workspace(&state.db, WorkspaceScoped::Source, source.id, auth_user.workspace_id).await;
+
+    let mut response = source_response(source, None);
     // New sources have no documents yet
     response.total_documents = 0;
     response.total_documents_ocr = 0;
@@ -173,12 +243,18 @@ pub async fn get_source(
         None
     };
 
-    let mut source_response: SourceResponse = source.into();
-    source_response.total_documents = total_documents;
-    source_response.total_documents_ocr = total_documents_ocr;
+    let schedule = state
+        .db
+        .get_source_schedule(source_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut response = source_response(source, schedule.as_ref());
+    response.total_documents = total_documents;
+    response.total_documents_ocr = total_documents_ocr;
 
     let response = SourceWithStats {
-        source: source_response,
+        source: response,
         recent_documents: recent_documents.into_iter().map(|d| d.into()).collect(),
         sync_progress,
     };
@@ -247,7 +323,13 @@ pub async fn update_source(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let mut response: SourceResponse = source.into();
+    let schedule = state
+        .db
+        .get_source_schedule(source_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut response = source_response(source, schedule.as_ref());
     response.total_documents = total_documents;
     response.total_documents_ocr = total_documents_ocr;
 
@@ -317,5 +399,29 @@ pub fn validate_config_for_type(
                 serde_json::from_value(config.clone()).map_err(|_| "Invalid S3 configuration")?;
             Ok(())
         }
+        SourceType::Imap => {
+            let config: crate::models::ImapSourceConfig =
+                serde_json::from_value(config.clone()).map_err(|_| "Invalid IMAP configuration")?;
+            crate::services::imap_service::ImapService::new(config).map_err(|_| "Invalid IMAP configuration")?;
+            Ok(())
+        }
+        SourceType::Dropbox => {
+            let config: crate::models::DropboxSourceConfig =
+                serde_json::from_value(config.clone()).map_err(|_| "Invalid Dropbox configuration")?;
+            crate::services::dropbox_service::DropboxService::new(config).map_err(|_| "Invalid Dropbox configuration")?;
+            Ok(())
+        }
+        SourceType::OneDrive => {
+            let config: crate::models::OneDriveSourceConfig =
+                serde_json::from_value(config.clone()).map_err(|_| "Invalid OneDrive configuration")?;
+            crate::services::onedrive_service::OneDriveService::new(config).map_err(|_| "Invalid OneDrive configuration")?;
+            Ok(())
+        }
+        SourceType::Sftp => {
+            let config: crate::models::SftpSourceConfig =
+                serde_json::from_value(config.clone()).map_err(|_| "Invalid SFTP configuration")?;
+            crate::services::sftp_service::SftpService::new(config).map_err(|_| "Invalid SFTP configuration")?;
+            Ok(())
+        }
     }
 }
\ No newline at end of file
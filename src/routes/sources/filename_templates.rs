@@ -0,0 +1,155 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::source::SourceError,
+    models::{
+        FilenameParsePreview, SourceFilenameTemplates, TestFilenameTemplatesRequest, UpdateFilenameTemplatesRequest,
+    },
+    services::filename_template,
+    AppState,
+};
+
+use super::schedule::get_own_source;
+
+/// At most this many sample file names per test
+const MAX_TEST_FILENAMES: usize = 100;
+
+/// Templates that read metadata from the names of the source's files
+#[utoipa::path(
+    get,
+    path = "/api/sources/{id}/filename-templates",
+    tag = "sources",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Source ID")
+    ),
+    responses(
+        (status = 200, description = "Filename templates of the source, in the order they are tried", body = Vec<String>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_filename_templates(
+    auth_user: AuthUser,
+    Path(source_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<String>>, SourceError> {
+    get_own_source(&state, auth_user.user.id, source_id).await?;
+    let templates = load_templates(&state, source_id).await?;
+    Ok(Json(templates))
+}
+
+/// Replace the filename templates of a source
+///
+/// Files the source ingests from then on get the date, correspondent, title, document
+/// type and tags the first matching template reads from their names.
+#[utoipa::path(
+    put,
+    path = "/api/sources/{id}/filename-templates",
+    tag = "sources",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Source ID")
+    ),
+    request_body = UpdateFilenameTemplatesRequest,
+    responses(
+        (status = 200, description = "Filename templates saved", body = Vec<String>),
+        (status = 400, description = "Invalid template or too many templates"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_filename_templates(
+    auth_user: AuthUser,
+    Path(source_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<UpdateFilenameTemplatesRequest>,
+) -> Result<Json<Vec<String>>, SourceError> {
+    get_own_source(&state, auth_user.user.id, source_id).await?;
+
+    let templates: Vec<String> = request.templates.iter().map(|template| template.trim().to_string()).collect();
+    filename_template::compile(&templates).map_err(SourceError::configuration_invalid)?;
+
+    let saved = state
+        .db
+        .set_source_filename_templates(source_id, &templates)
+        .await
+        .map_err(|e| SourceError::connection_failed(format!("Failed to save filename templates: {}", e)))?;
+
+    info!("Updated the filename templates of source {}", source_id);
+    Ok(Json(saved.map(|saved| saved.templates).unwrap_or_default()))
+}
+
+/// Preview what the templates read from sample file names, without ingesting anything
+#[utoipa::path(
+    post,
+    path = "/api/sources/{id}/filename-templates/test",
+    tag = "sources",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Source ID")
+    ),
+    request_body = TestFilenameTemplatesRequest,
+    responses(
+        (status = 200, description = "What was parsed from each file name", body = Vec<FilenameParsePreview>),
+        (status = 400, description = "Invalid template, or no or too many file names"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn test_filename_templates(
+    auth_user: AuthUser,
+    Path(source_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TestFilenameTemplatesRequest>,
+) -> Result<Json<Vec<FilenameParsePreview>>, SourceError> {
+    get_own_source(&state, auth_user.user.id, source_id).await?;
+
+    if request.filenames.is_empty() || request.filenames.len() > MAX_TEST_FILENAMES {
+        return Err(SourceError::configuration_invalid(format!(
+            "Give from 1 to {} file names to test",
+            MAX_TEST_FILENAMES
+        )));
+    }
+
+    let templates = match request.templates {
+        Some(templates) => templates,
+        None => load_templates(&state, source_id).await?,
+    };
+    let templates = filename_template::compile(&templates).map_err(SourceError::configuration_invalid)?;
+
+    let previews = request
+        .filenames
+        .into_iter()
+        .map(|filename| FilenameParsePreview {
+            parsed: filename_template::parse_filename(&templates, &filename),
+            filename,
+        })
+        .collect();
+    Ok(Json(previews))
+}
+
+async fn load_templates(state: &AppState, source_id: Uuid) -> Result<Vec<String>, SourceError> {
+    let templates: Option<SourceFilenameTemplates> = state
+        .db
+        .get_source_filename_templates(source_id)
+        .await
+        .map_err(|e| SourceError::connection_failed(format!("Failed to retrieve filename templates: {}", e)))?;
+    Ok(templates.map(|templates| templates.templates).unwrap_or_default())
+}
@@ -6,12 +6,22 @@ pub mod crud;
 pub mod sync;
 pub mod validation;
 pub mod estimation;
+pub mod s3_events;
+pub mod schedule;
+pub mod runs;
+pub mod simulation;
+pub mod filename_templates;
 
 // Re-export commonly used functions and types for backward compatibility
 pub use crud::*;
 pub use sync::*;
 pub use validation::*;
 pub use estimation::*;
+pub use s3_events::*;
+pub use schedule::*;
+pub use runs::*;
+pub use simulation::*;
+pub use filename_templates::*;
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
@@ -28,10 +38,19 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/{id}/sync/progress/ws", get(sync_progress_websocket))
         .route("/{id}/sync/status", get(get_sync_status))
         .route("/{id}/deep-scan", post(trigger_deep_scan))
+        .route("/{id}/s3-events", post(receive_s3_events))
+        .route("/{id}/outgoing-changes", get(list_outgoing_changes))
+        .route("/{id}/schedule", get(get_source_schedule))
+        .route("/{id}/schedule", put(update_source_schedule))
+        .route("/{id}/runs", get(list_source_sync_runs))
+        .route("/{id}/filename-templates", get(get_filename_templates))
+        .route("/{id}/filename-templates", put(update_filename_templates))
+        .route("/{id}/filename-templates/test", post(test_filename_templates))
         
         // Validation operations
         .route("/{id}/validate", post(validate_source))
         .route("/test", post(test_connection_with_config))
+        .route("/{id}/simulate", post(simulate_source_rules))
         
         // Estimation operations
         .route("/{id}/estimate", get(estimate_crawl))
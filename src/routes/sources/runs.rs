@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::source::SourceError,
+    models::{SourceSyncRun, SourceSyncRunListQuery},
+    AppState,
+};
+
+const DEFAULT_LIST_LIMIT: i64 = 20;
+const MAX_LIST_LIMIT: i64 = 100;
+
+/// Past syncs of a source, newest first, with what each did to the files it saw and
+/// why files failed
+#[utoipa::path(
+    get,
+    path = "/api/sources/{id}/runs",
+    tag = "sources",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Source ID"),
+        SourceSyncRunListQuery
+    ),
+    responses(
+        (status = 200, description = "Sync runs of the source", body = Vec<SourceSyncRun>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_source_sync_runs(
+    auth_user: AuthUser,
+    Path(source_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SourceSyncRunListQuery>,
+) -> Result<Json<Vec<SourceSyncRun>>, SourceError> {
+    state
+        .db
+        .get_source(auth_user.user.id, source_id)
+        .await
+        .map_err(|e| SourceError::connection_failed(format!("Failed to retrieve source: {}", e)))?
+        .ok_or_else(|| SourceError::not_found_by_id(source_id))?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let runs = state
+        .db
+        .list_source_sync_runs(source_id, limit, offset)
+        .await
+        .map_err(|e| SourceError::connection_failed(format!("Failed to retrieve sync runs: {}", e)))?;
+
+    Ok(Json(runs))
+}
@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::{
+    errors::panic::spawn_guarded,
+    models::{S3SourceConfig, SourceType},
+    services::s3_event_service::{self, S3EventService},
+    AppState,
+};
+
+/// Receive a bucket notification for an S3 source. Authenticated with the source's
+/// webhook secret instead of a user token, so it can be set as a bucket webhook target.
+#[utoipa::path(
+    post,
+    path = "/api/sources/{id}/s3-events",
+    tag = "sources",
+    params(
+        ("id" = Uuid, Path, description = "Source ID")
+    ),
+    request_body(content = String, description = "S3 event notification (S3 event JSON or SNS envelope)", content_type = "application/json"),
+    responses(
+        (status = 202, description = "Notification accepted; new objects are ingested in the background"),
+        (status = 400, description = "Body is not an S3 event notification"),
+        (status = 401, description = "Missing or wrong webhook secret"),
+        (status = 404, description = "No S3 source with a webhook secret has this ID"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn receive_s3_events(
+    State(state): State<Arc<AppState>>,
+    Path(source_id): Path<Uuid>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
+    let source = state
+        .db
+        .get_source_by_id(source_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load source {} for S3 notification: {}", source_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .filter(|source| source.source_type == SourceType::S3)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let config: S3SourceConfig = serde_json::from_value(source.config.clone()).map_err(|e| {
+        error!("Invalid S3 config for source {}: {}", source_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if config.notifications.as_ref().and_then(|n| n.webhook_secret.as_deref()).is_none_or(str::is_empty) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let authorization = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+    if !s3_event_service::webhook_authorized(&config, authorization) {
+        warn!("Rejected S3 notification for source {} with a wrong webhook secret", source_id);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let events = s3_event_service::parse_notification(&body).map_err(|e| {
+        warn!("Unreadable S3 notification for source {}: {}", source_id, e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let accepted = if source.enabled { events.len() } else { 0 };
+    if accepted > 0 {
+        let service = S3EventService::new(state.clone());
+        spawn_guarded(format!("S3 notification for source {}", source_id), async move {
+            if let Err(e) = service.ingest_events(&source, events).await {
+                error!("Failed to handle S3 notification for source {}: {}", source_id, e);
+            }
+        });
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({ "accepted": accepted }))))
+}
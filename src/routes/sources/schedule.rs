@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::Utc;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::source::SourceError,
+    models::{Source, SourceSchedule, SourceScheduleResponse, UpdateSourceScheduleRequest},
+    scheduling::{cron::CronSchedule, sync_schedule},
+    AppState,
+};
+
+/// A week; longer syncs are better split up
+const MAX_RUNTIME_LIMIT_MINUTES: i32 = 7 * 24 * 60;
+
+/// When a source syncs on its own, its next scheduled sync and how long the last one took
+#[utoipa::path(
+    get,
+    path = "/api/sources/{id}/schedule",
+    tag = "sources",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Source ID")
+    ),
+    responses(
+        (status = 200, description = "Schedule of the source", body = SourceScheduleResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_source_schedule(
+    auth_user: AuthUser,
+    Path(source_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SourceScheduleResponse>, SourceError> {
+    let source = get_own_source(&state, auth_user.user.id, source_id).await?;
+    let schedule = state
+        .db
+        .get_source_schedule(source_id)
+        .await
+        .map_err(|e| SourceError::connection_failed(format!("Failed to retrieve source schedule: {}", e)))?;
+
+    Ok(Json(schedule_response(&source, schedule.as_ref())))
+}
+
+/// Replace the schedule of a source
+///
+/// Without a cron expression the source syncs every `sync_interval_minutes` of its
+/// config. Either way it only syncs on its own while `auto_sync` is on.
+#[utoipa::path(
+    put,
+    path = "/api/sources/{id}/schedule",
+    tag = "sources",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Source ID")
+    ),
+    request_body = UpdateSourceScheduleRequest,
+    responses(
+        (status = 200, description = "Schedule saved", body = SourceScheduleResponse),
+        (status = 400, description = "Invalid cron expression, quiet hours or max runtime"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_source_schedule(
+    auth_user: AuthUser,
+    Path(source_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<UpdateSourceScheduleRequest>,
+) -> Result<Json<SourceScheduleResponse>, SourceError> {
+    let source = get_own_source(&state, auth_user.user.id, source_id).await?;
+
+    let cron_expression = request
+        .cron_expression
+        .as_deref()
+        .map(str::trim)
+        .filter(|expression| !expression.is_empty());
+    if let Some(expression) = cron_expression {
+        CronSchedule::parse(expression).map_err(SourceError::configuration_invalid)?;
+    }
+
+    let quiet_hours = match (request.quiet_hours_start.as_deref(), request.quiet_hours_end.as_deref()) {
+        (None, None) => None,
+        (Some(start), Some(end)) => {
+            let start = sync_schedule::parse_time_of_day(start).map_err(SourceError::configuration_invalid)?;
+            let end = sync_schedule::parse_time_of_day(end).map_err(SourceError::configuration_invalid)?;
+            if start == end {
+                return Err(SourceError::configuration_invalid("Quiet hours must not start and end at the same time"));
+            }
+            Some((start, end))
+        }
+        _ => {
+            return Err(SourceError::configuration_invalid("Quiet hours need both a start and an end"));
+        }
+    };
+
+    if let Some(minutes) = request.max_runtime_minutes {
+        if !(1..=MAX_RUNTIME_LIMIT_MINUTES).contains(&minutes) {
+            return Err(SourceError::configuration_invalid(format!(
+                "Max runtime must be from 1 to {} minutes",
+                MAX_RUNTIME_LIMIT_MINUTES
+            )));
+        }
+    }
+
+    let schedule = state
+        .db
+        .set_source_schedule(source_id, cron_expression, quiet_hours, request.max_runtime_minutes)
+        .await
+        .map_err(|e| SourceError::connection_failed(format!("Failed to save source schedule: {}", e)))?;
+
+    info!("Updated the sync schedule of source {}", source_id);
+    Ok(Json(schedule_response(&source, Some(&schedule))))
+}
+
+pub(super) async fn get_own_source(state: &AppState, user_id: Uuid, source_id: Uuid) -> Result<Source, SourceError> {
+    state
+        .db
+        .get_source(user_id, source_id)
+        .await
+        .map_err(|e| SourceError::connection_failed(format!("Failed to retrieve source: {}", e)))?
+        .ok_or_else(|| SourceError::not_found_by_id(source_id))
+}
+
+fn schedule_response(source: &Source, schedule: Option<&SourceSchedule>) -> SourceScheduleResponse {
+    let time_of_day = |time: Option<chrono::NaiveTime>| time.map(|time| time.format("%H:%M").to_string());
+    SourceScheduleResponse {
+        cron_expression: schedule.and_then(|schedule| schedule.cron_expression.clone()),
+        quiet_hours_start: time_of_day(schedule.and_then(|schedule| schedule.quiet_hours_start)),
+        quiet_hours_end: time_of_day(schedule.and_then(|schedule| schedule.quiet_hours_end)),
+        max_runtime_minutes: schedule.and_then(|schedule| schedule.max_runtime_minutes),
+        next_sync_at: sync_schedule::next_sync_at(source, schedule, Utc::now()).ok().flatten(),
+        last_sync_started_at: schedule.and_then(|schedule| schedule.last_sync_started_at),
+        last_sync_duration_ms: schedule.and_then(|schedule| schedule.last_sync_duration_ms),
+    }
+}
@@ -0,0 +1,90 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{SimulateSourceRulesRequest, SourceRuleSimulation},
+    services::source_rule_simulation::{self, RuleSample},
+    AppState,
+};
+
+/// Run a source's filter and label rules against a sample without syncing
+#[utoipa::path(
+    post,
+    path = "/api/sources/{id}/simulate",
+    tag = "sources",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Source ID")
+    ),
+    request_body = SimulateSourceRulesRequest,
+    responses(
+        (status = 200, description = "Rule-by-rule outcome for the sample", body = SourceRuleSimulation),
+        (status = 400, description = "No sample path or invalid configuration"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source or document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn simulate_source_rules(
+    auth_user: AuthUser,
+    Path(source_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SimulateSourceRulesRequest>,
+) -> Result<Json<SourceRuleSimulation>, StatusCode> {
+    let source = state
+        .db
+        .get_source(auth_user.user.id, source_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get source {}: {}", source_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut sample = RuleSample {
+        path: request.path.unwrap_or_default(),
+        from: request.from,
+        subject: request.subject,
+    };
+
+    if let Some(document_id) = request.document_id {
+        let document = state
+            .db
+            .get_document_by_id(document_id, auth_user.user.id, auth_user.user.role)
+            .await
+            .map_err(|e| {
+                error!("Failed to get document {}: {}", document_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+
+        if sample.path.is_empty() {
+            sample.path = document.source_path.clone().unwrap_or_else(|| document.original_filename.clone());
+        }
+        let metadata = document.source_metadata.as_ref();
+        let metadata_field = |key: &str| metadata.and_then(|m| m.get(key)).and_then(|v| v.as_str()).map(str::to_string);
+        sample.from = sample.from.or_else(|| metadata_field("email_from"));
+        sample.subject = sample.subject.or_else(|| metadata_field("email_subject"));
+    }
+
+    if sample.path.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let config = request.config.unwrap_or(source.config);
+    let simulation = source_rule_simulation::simulate(source.source_type, &config, &sample).map_err(|e| {
+        warn!("Rule simulation for source {} failed: {}", source_id, e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    Ok(Json(simulation))
+}
@@ -1,9 +1,10 @@
 use axum::{
-    extract::{Path, State, WebSocketUpgrade},
+    extract::{Path, Query, State, WebSocketUpgrade},
     extract::ws::{WebSocket, Message},
     http::{StatusCode, HeaderMap},
-    response::{Json, Response},
+    response::{IntoResponse, Json, Response},
 };
+use serde::Deserialize;
 use std::sync::Arc;
 use uuid::Uuid;
 use tracing::{error, info};
@@ -11,13 +12,23 @@ use std::time::Duration;
 
 use crate::{
     auth::AuthUser,
-    models::SourceStatus,
+    models::{SourceStatus, SourceSyncPreview},
+    services::source_sync_preview,
     services::webdav::{SyncProgress, SyncPhase},
     AppState,
 };
 
 // Removed WebSocketAuthQuery - using secure header-based authentication instead
 
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+pub struct TriggerSyncQuery {
+    /// Walk the source and report what would be ingested, skipped and deleted without changing anything
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Most entries to list in a dry run (default 1000); the counts cover every file
+    pub limit: Option<usize>,
+}
+
 /// Trigger a sync for a source
 #[utoipa::path(
     post,
@@ -27,22 +38,26 @@ use crate::{
         ("bearer_auth" = [])
     ),
     params(
-        ("id" = Uuid, Path, description = "Source ID")
+        ("id" = Uuid, Path, description = "Source ID"),
+        TriggerSyncQuery
     ),
     responses(
-        (status = 200, description = "Sync triggered successfully"),
+        (status = 200, description = "Sync triggered successfully, or the dry-run preview", body = SourceSyncPreview),
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Source not found"),
         (status = 409, description = "Source is already syncing"),
         (status = 500, description = "Internal server error"),
-        (status = 501, description = "Not implemented - Source type not supported")
+        (status = 501, description = "Not implemented - Source type not supported"),
+        (status = 503, description = "Source syncs are paused"),
+        (status = 502, description = "Dry run could not list the source")
     )
 )]
 pub async fn trigger_sync(
     auth_user: AuthUser,
     Path(source_id): Path<Uuid>,
+    Query(query): Query<TriggerSyncQuery>,
     State(state): State<Arc<AppState>>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<Response, StatusCode> {
     let source = state
         .db
         .get_source(auth_user.user.id, source_id)
@@ -50,6 +65,19 @@ pub async fn trigger_sync(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
+    if query.dry_run {
+        if !source_sync_preview::supports(source.source_type) {
+            return Err(StatusCode::NOT_IMPLEMENTED);
+        }
+        let preview = source_sync_preview::preview(&state.db, &source, query.limit.unwrap_or(1000))
+            .await
+            .map_err(|e| {
+                error!("Sync dry run for source {} failed: {}", source_id, e);
+                StatusCode::BAD_GATEWAY
+            })?;
+        return Ok(Json(preview).into_response());
+    }
+
     // Trigger sync using the universal source scheduler
     // The scheduler will handle all status checks and atomic operations
     if let Some(scheduler) = &state.source_scheduler {
@@ -64,6 +92,8 @@ pub async fn trigger_sync(
                 // Map specific errors to appropriate HTTP status codes
                 if error_msg.contains("already syncing") || error_msg.contains("already running") {
                     return Err(StatusCode::CONFLICT);
+                } else if error_msg.contains("paused") {
+                    return Err(StatusCode::SERVICE_UNAVAILABLE);
                 } else if error_msg.contains("not found") {
                     return Err(StatusCode::NOT_FOUND);
                 } else {
@@ -105,7 +135,7 @@ pub async fn trigger_sync(
         }
     }
 
-    Ok(StatusCode::OK)
+    Ok(StatusCode::OK.into_response())
 }
 
 /// Stop sync for a source
@@ -366,16 +396,8 @@ pub async fn trigger_deep_scan(
                                     }
                                     
                                     // Send success notification
+                                    let locale = crate::services::i18n::locale_of(&state_clone.db, user_id).await;
                                     let notification = crate::models::CreateNotification {
-                                        notification_type: "success".to_string(),
-                                        title: "Deep Scan Completed".to_string(),
-                                        message: format!(
-                                            "Smart deep scan of {} completed successfully. {} files processed, {} directories tracked in {:.1} minutes.",
-                                            source_name,
-                                            files_processed,
-                                            total_directories_tracked,
-                                            duration.num_seconds() as f64 / 60.0
-                                        ),
                                         action_url: Some("/documents".to_string()),
                                         metadata: Some(serde_json::json!({
                                             "source_id": source_id_clone,
@@ -383,6 +405,17 @@ pub async fn trigger_deep_scan(
                                             "files_processed": files_processed,
                                             "duration_seconds": duration.num_seconds()
                                         })),
+                                        ..crate::services::i18n::notification(
+                                            &locale,
+                                            "success",
+                                            "notification.deep_scan_completed",
+                                            &[
+                                                ("source", source_name.clone()),
+                                                ("files", files_processed.to_string()),
+                                                ("directories", total_directories_tracked.to_string()),
+                                                ("minutes", format!("{:.1}", duration.num_seconds() as f64 / 60.0)),
+                                            ],
+                                        )
                                     };
                                     
                                     if let Err(e) = state_clone.db.create_notification(user_id, &notification).await {
@@ -412,16 +445,15 @@ pub async fn trigger_deep_scan(
                                     }
                                     
                                     // Send error notification
+                                    let locale = crate::services::i18n::locale_of(&state_clone.db, user_id).await;
                                     let notification = crate::models::CreateNotification {
-                                        notification_type: "error".to_string(),
-                                        title: "Deep Scan Failed".to_string(),
-                                        message: format!("Deep scan of {} failed: {}", source_name, e),
                                         action_url: Some("/sources".to_string()),
                                         metadata: Some(serde_json::json!({
                                             "source_id": source_id_clone,
                                             "scan_type": "deep_scan",
                                             "error": e.to_string()
                                         })),
+                                        ..crate::services::i18n::notification(&locale, "error", "notification.deep_scan_failed", &[("source", source_name.clone()), ("error", e.to_string())])
                                     };
                                     
                                     if let Err(e) = state_clone.db.create_notification(user_id, &notification).await {
@@ -518,10 +550,11 @@ pub async fn sync_progress_websocket(
     // Extract and verify token from Sec-WebSocket-Protocol header for secure WebSocket auth
     let (token, auth_protocol) = extract_websocket_token_and_protocol(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
     
-    let claims = crate::auth::verify_jwt(&token, &state.config.jwt_secret)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let (user_id, session_id) = crate::auth::authenticate_jwt(&state, &token)
+        .await
+        .map_err(|response| response.status())?;
     
-    let user = state.db.get_user_by_id(claims.sub).await
+    let user = state.db.get_user_by_id(user_id).await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::UNAUTHORIZED)?;
     
@@ -536,11 +569,33 @@ pub async fn sync_progress_websocket(
     // Upgrade the connection to WebSocket with protocol acknowledgment
     Ok(ws
         .protocols([auth_protocol.clone()])
-        .on_upgrade(move |socket| handle_websocket(socket, source_id, state)))
+        .on_upgrade(move |socket| handle_websocket(socket, source_id, user.id, session_id, state)))
+}
+
+/// How often an open progress socket re-checks that its user may still see the source
+const WEBSOCKET_ACCESS_RECHECK: Duration = Duration::from_secs(30);
+
+/// Whether the user still exists, is still logged in and may see the source
+async fn websocket_access_allowed(state: &AppState, user_id: Uuid, session_id: Option<Uuid>, source_id: Uuid) -> bool {
+    if let Some(session_id) = session_id {
+        if !matches!(state.db.get_active_session(session_id).await, Ok(Some(_))) {
+            return false;
+        }
+    }
+    match state.db.get_user_by_id(user_id).await {
+        Ok(Some(user)) => matches!(state.db.get_source(user.id, source_id).await, Ok(Some(_))),
+        _ => false,
+    }
 }
 
 /// Handle WebSocket connection for sync progress updates
-async fn handle_websocket(mut socket: WebSocket, source_id: Uuid, state: Arc<AppState>) {
+async fn handle_websocket(
+    mut socket: WebSocket,
+    source_id: Uuid,
+    user_id: Uuid,
+    session_id: Option<Uuid>,
+    state: Arc<AppState>,
+) {
     info!("WebSocket connection established for source {}", source_id);
     
     // Send connection confirmation
@@ -558,8 +613,27 @@ async fn handle_websocket(mut socket: WebSocket, source_id: Uuid, state: Arc<App
     }
     
     let progress_tracker = state.sync_progress_tracker.clone();
+    let mut access_checked = std::time::Instant::now();
     
     loop {
+        // Access may be revoked while the socket is open, e.g. by deleting the user or source
+        // or ending the session
+        if access_checked.elapsed() >= WEBSOCKET_ACCESS_RECHECK {
+            if !websocket_access_allowed(&state, user_id, session_id, source_id).await {
+                info!("Closing WebSocket for source {}: user {} lost access", source_id, user_id);
+                let revoked_msg = serde_json::json!({
+                    "type": "error",
+                    "data": {
+                        "message": "Access to this source was revoked",
+                        "error_type": "access_revoked"
+                    }
+                });
+                let _ = socket.send(Message::Text(revoked_msg.to_string().into())).await;
+                break;
+            }
+            access_checked = std::time::Instant::now();
+        }
+
         // Check for progress update
         let progress_info = progress_tracker.get_progress(source_id);
         
@@ -663,7 +737,7 @@ fn extract_websocket_token(headers: &HeaderMap) -> Option<String> {
 
 /// Extract JWT token and protocol string from WebSocket headers
 /// Returns both the token and the original protocol for handshake acknowledgment
-fn extract_websocket_token_and_protocol(headers: &HeaderMap) -> Option<(String, String)> {
+pub(crate) fn extract_websocket_token_and_protocol(headers: &HeaderMap) -> Option<(String, String)> {
     // Check for token in Sec-WebSocket-Protocol header (most secure)
     if let Some(protocol_header) = headers.get("sec-websocket-protocol") {
         if let Ok(protocols) = protocol_header.to_str() {
@@ -731,4 +805,46 @@ pub async fn get_sync_status(
     let progress_info = state.sync_progress_tracker.get_progress(source_id);
     
     Ok(Json(progress_info))
-}
\ No newline at end of file
+}
+/// List recent deletes and renames to write back to a bidirectional WebDAV source,
+/// including those not applied because the file changed on the server
+#[utoipa::path(
+    get,
+    path = "/api/sources/{id}/outgoing-changes",
+    tag = "sources",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Source ID")
+    ),
+    responses(
+        (status = 200, description = "Outgoing changes, newest first", body = [crate::models::WebDAVOutgoingChange]),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_outgoing_changes(
+    auth_user: AuthUser,
+    Path(source_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<crate::models::WebDAVOutgoingChange>>, StatusCode> {
+    let _source = state
+        .db
+        .get_source(auth_user.user.id, source_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let changes = state
+        .db
+        .list_webdav_outgoing_changes(source_id, 200)
+        .await
+        .map_err(|e| {
+            error!("Failed to list outgoing changes for source {}: {}", source_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(changes))
+}
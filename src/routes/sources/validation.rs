@@ -124,6 +124,44 @@ pub async fn test_connection(
                 }))),
             }
         }
+        SourceType::Imap => {
+            // Test IMAP login and mailbox access
+            let config: crate::models::ImapSourceConfig = serde_json::from_value(source.config)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            Ok(Json(imap_test_result(config).await))
+        }
+        SourceType::Dropbox => {
+            // Test the refresh token and account access
+            let config: crate::models::DropboxSourceConfig = serde_json::from_value(source.config)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            let refresh_token = config.refresh_token.clone();
+
+            Ok(Json(cloud_drive_test_result(
+                "Dropbox",
+                crate::services::dropbox_service::DropboxService::new(config),
+                &refresh_token,
+            ).await))
+        }
+        SourceType::OneDrive => {
+            // Test the refresh token and drive access
+            let config: crate::models::OneDriveSourceConfig = serde_json::from_value(source.config)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            let refresh_token = config.refresh_token.clone();
+
+            Ok(Json(cloud_drive_test_result(
+                "OneDrive",
+                crate::services::onedrive_service::OneDriveService::new(config),
+                &refresh_token,
+            ).await))
+        }
+        SourceType::Sftp => {
+            // Test login and directory listing, reporting the server's host key
+            let config: crate::models::SftpSourceConfig = serde_json::from_value(source.config)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            Ok(Json(sftp_test_result(config).await))
+        }
     }
 }
 
@@ -220,6 +258,105 @@ pub async fn test_connection_with_config(
                 }))),
             }
         }
+        SourceType::Imap => {
+            // Test IMAP login and mailbox access
+            let config: crate::models::ImapSourceConfig = serde_json::from_value(request.config)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+            Ok(Json(imap_test_result(config).await))
+        }
+        SourceType::Dropbox => {
+            // Test the refresh token and account access
+            let config: crate::models::DropboxSourceConfig = serde_json::from_value(request.config)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+            let refresh_token = config.refresh_token.clone();
+
+            Ok(Json(cloud_drive_test_result(
+                "Dropbox",
+                crate::services::dropbox_service::DropboxService::new(config),
+                &refresh_token,
+            ).await))
+        }
+        SourceType::OneDrive => {
+            // Test the refresh token and drive access
+            let config: crate::models::OneDriveSourceConfig = serde_json::from_value(request.config)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+            let refresh_token = config.refresh_token.clone();
+
+            Ok(Json(cloud_drive_test_result(
+                "OneDrive",
+                crate::services::onedrive_service::OneDriveService::new(config),
+                &refresh_token,
+            ).await))
+        }
+        SourceType::Sftp => {
+            // Test login and directory listing, reporting the server's host key
+            let config: crate::models::SftpSourceConfig = serde_json::from_value(request.config)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+            Ok(Json(sftp_test_result(config).await))
+        }
+    }
+}
+
+async fn imap_test_result(config: crate::models::ImapSourceConfig) -> serde_json::Value {
+    match crate::services::imap_service::ImapService::new(config) {
+        Ok(service) => match service.test_connection().await {
+            Ok(message) => serde_json::json!({
+                "success": true,
+                "message": message
+            }),
+            Err(e) => serde_json::json!({
+                "success": false,
+                "message": format!("IMAP test failed: {}", e)
+            }),
+        },
+        Err(e) => serde_json::json!({
+            "success": false,
+            "message": format!("IMAP configuration error: {}", e)
+        }),
+    }
+}
+
+async fn sftp_test_result(config: crate::models::SftpSourceConfig) -> serde_json::Value {
+    match crate::services::sftp_service::SftpService::new(config) {
+        Ok(service) => match service.test_connection().await {
+            Ok(message) => serde_json::json!({
+                "success": true,
+                "message": message
+            }),
+            Err(e) => serde_json::json!({
+                "success": false,
+                "message": format!("SFTP test failed: {}", e)
+            }),
+        },
+        Err(e) => serde_json::json!({
+            "success": false,
+            "message": format!("SFTP configuration error: {}", e)
+        }),
+    }
+}
+
+async fn cloud_drive_test_result<C: crate::services::cloud_drive::CloudDriveClient>(
+    provider: &str,
+    client: anyhow::Result<C>,
+    refresh_token: &str,
+) -> serde_json::Value {
+    match client {
+        Ok(client) => match crate::services::cloud_drive::test_connection(&client, refresh_token).await {
+            Ok(message) => serde_json::json!({
+                "success": true,
+                "message": message
+            }),
+            Err(e) => serde_json::json!({
+                "success": false,
+                "message": format!("{} test failed: {}", provider, e)
+            }),
+        },
+        Err(e) => serde_json::json!({
+            "success": false,
+            "message": format!("{} configuration error: {}", provider, e)
+        }),
     }
 }
 
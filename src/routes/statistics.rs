@@ -0,0 +1,248 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::{debug, error};
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        storage_growth, AccessStatistics, AccessStatisticsQuery, Dashboard, Statistics, StatisticsQuery,
+        UnusedDocumentsQuery, UnusedDocumentsResponse, UpdateDashboardRequest, UserRole,
+    },
+    routes::queue::require_admin,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(get_statistics))
+        .route("/dashboard", get(get_dashboard).put(update_dashboard))
+        .route("/access", get(get_access_statistics))
+        .route("/access/unused", get(get_unused_documents))
+}
+
+fn internal_error(what: &str, e: anyhow::Error) -> StatusCode {
+    error!("Failed to compute {} statistics: {}", what, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Uploads, OCR throughput, storage growth, top correspondents and labels over time
+///
+/// Series have one entry per bucket of the range, empty buckets included. Admins see
+/// the whole library, broken down per user, or one user's documents with `user_id`;
+/// other users see their own documents only.
+#[utoipa::path(
+    get,
+    path = "/api/statistics",
+    tag = "statistics",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(StatisticsQuery),
+    responses(
+        (status = 200, description = "Statistics over the range", body = Statistics),
+        (status = 400, description = "The range ends before it starts or needs too many buckets"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "user_id given by a user who is not an admin"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_statistics(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<StatisticsQuery>,
+) -> Result<Json<Statistics>, StatusCode> {
+    let (from, to, bucket) = query.range(chrono::Utc::now().date_naive()).map_err(|e| {
+        debug!("Invalid statistics range: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let user_id = if auth_user.user.role == UserRole::Admin {
+        query.user_id
+    } else if query.user_id.is_some_and(|id| id != auth_user.user.id) {
+        return Err(StatusCode::FORBIDDEN);
+    } else {
+        Some(auth_user.user.id)
+    };
+    let top = query.top();
+
+    let db = &state.db;
+    let (ingested, ocr, bytes_before, top_correspondents, labels, users) = tokio::try_join!(
+        async { db.get_ingestion_statistics(user_id, from, to, bucket).await.map_err(|e| internal_error("ingestion", e)) },
+        async { db.get_ocr_statistics(user_id, from, to, bucket).await.map_err(|e| internal_error("OCR", e)) },
+        async { db.get_stored_bytes_before(user_id, from).await.map_err(|e| internal_error("storage", e)) },
+        async {
+            db.get_top_correspondent_statistics(user_id, from, to, top)
+                .await
+                .map_err(|e| internal_error("correspondent", e))
+        },
+        async { db.get_label_statistics(user_id, from, to, top).await.map_err(|e| internal_error("label", e)) },
+        async { db.get_user_statistics(user_id, from, to).await.map_err(|e| internal_error("user", e)) },
+    )?;
+
+    Ok(Json(Statistics {
+        from,
+        to,
+        bucket,
+        user_id,
+        storage: storage_growth(bytes_before, &ingested),
+        ingested,
+        ocr,
+        top_correspondents,
+        labels,
+        users,
+    }))
+}
+
+/// Views and downloads over time, the most opened documents and who opened them (admin only)
+///
+/// Views of a document's details, preview or file and downloads are counted per day,
+/// including those through share links and guest portals. With `document_id` the
+/// activity series is that document's heatmap.
+#[utoipa::path(
+    get,
+    path = "/api/statistics/access",
+    tag = "statistics",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(AccessStatisticsQuery),
+    responses(
+        (status = 200, description = "Access statistics over the range", body = AccessStatistics),
+        (status = 400, description = "The range ends before it starts or needs too many buckets"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_access_statistics(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<AccessStatisticsQuery>,
+) -> Result<Json<AccessStatistics>, StatusCode> {
+    require_admin(&auth_user)?;
+    let (from, to, bucket) = query.range(chrono::Utc::now().date_naive()).map_err(|e| {
+        debug!("Invalid access statistics range: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let (user_id, document_id) = (query.user_id, query.document_id);
+
+    let db = &state.db;
+    let (activity, most_accessed, users) = tokio::try_join!(
+        async {
+            db.get_access_activity(user_id, document_id, from, to, bucket)
+                .await
+                .map_err(|e| internal_error("access", e))
+        },
+        async {
+            db.get_most_accessed_documents(user_id, document_id, from, to, query.top())
+                .await
+                .map_err(|e| internal_error("most accessed document", e))
+        },
+        async {
+            db.get_user_access_statistics(user_id, document_id, from, to)
+                .await
+                .map_err(|e| internal_error("user access", e))
+        },
+    )?;
+
+    Ok(Json(AccessStatistics { from, to, bucket, user_id, document_id, activity, most_accessed, users }))
+}
+
+/// Documents nobody opened since they were uploaded, oldest first (admin only)
+///
+/// Candidates for archival. Views before access statistics were recorded only count
+/// when the audit log still had them.
+#[utoipa::path(
+    get,
+    path = "/api/statistics/access/unused",
+    tag = "statistics",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(UnusedDocumentsQuery),
+    responses(
+        (status = 200, description = "Unused documents with their total count and size", body = UnusedDocumentsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_unused_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<UnusedDocumentsQuery>,
+) -> Result<Json<UnusedDocumentsResponse>, StatusCode> {
+    require_admin(&auth_user)?;
+    let (documents, total, total_bytes) = state
+        .db
+        .get_unused_documents(query.user_id, query.uploaded_before(chrono::Utc::now()), query.limit(), query.offset())
+        .await
+        .map_err(|e| internal_error("unused document", e))?;
+
+    Ok(Json(UnusedDocumentsResponse { documents, total, total_bytes }))
+}
+
+/// The current user's dashboard, or the default one until they save their own
+#[utoipa::path(
+    get,
+    path = "/api/statistics/dashboard",
+    tag = "statistics",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Dashboard widgets in display order", body = Dashboard),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_dashboard(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Dashboard>, StatusCode> {
+    let dashboard = state.db.get_user_dashboard(auth_user.user.id).await.map_err(|e| {
+        error!("Failed to get dashboard of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(dashboard.unwrap_or_default()))
+}
+
+/// Save the current user's dashboard
+#[utoipa::path(
+    put,
+    path = "/api/statistics/dashboard",
+    tag = "statistics",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = UpdateDashboardRequest,
+    responses(
+        (status = 200, description = "Dashboard saved", body = Dashboard),
+        (status = 400, description = "Too many widgets, or a widget with a duplicate id, long title or invalid range"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_dashboard(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateDashboardRequest>,
+) -> Result<Json<Dashboard>, StatusCode> {
+    if let Err(e) = request.validate() {
+        debug!("Invalid dashboard: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let dashboard = state.db.save_user_dashboard(auth_user.user.id, &request.widgets).await.map_err(|e| {
+        error!("Failed to save dashboard of user {}: {}", auth_user.user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(dashboard))
+}
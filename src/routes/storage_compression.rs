@@ -0,0 +1,359 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{Json, Response},
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        CreateStorageCompressionPolicyRequest, StorageCompressionPolicy, StorageCompressionSummary,
+        UpdateStorageCompressionPolicyRequest,
+    },
+    routes::queue::require_admin,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/policies", get(list_compression_policies).post(create_compression_policy))
+        .route(
+            "/policies/{id}",
+            get(get_compression_policy)
+                .put(update_compression_policy)
+                .delete(delete_compression_policy),
+        )
+        .route("/summary", get(get_compression_summary))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+fn map_write_error(e: anyhow::Error) -> StatusCode {
+    if e.to_string().contains("duplicate key") {
+        debug!("Rejected compression policy with a name already in use");
+        StatusCode::CONFLICT
+    } else {
+        internal_error("Failed to save compression policy", e)
+    }
+}
+
+/// Policies recompressing stored scans after OCR (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/storage/compression/policies",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Compression policies", body = Vec<StorageCompressionPolicy>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_compression_policies(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<StorageCompressionPolicy>>, StatusCode> {
+    require_admin(&auth_user)?;
+    let policies = state
+        .db
+        .get_storage_compression_policies()
+        .await
+        .map_err(|e| internal_error("Failed to list compression policies", e))?;
+
+    Ok(Json(policies))
+}
+
+/// Define a compression policy (admin only)
+///
+/// Files of the policy's types at least `min_file_size_bytes` large are recompressed
+/// once their OCR completes, downsampled to `target_dpi`. The original can be
+/// downloaded for `keep_original_days`, or until the document is deleted when not set.
+#[utoipa::path(
+    post,
+    path = "/api/storage/compression/policies",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateStorageCompressionPolicyRequest,
+    responses(
+        (status = 201, description = "Compression policy created", body = StorageCompressionPolicy),
+        (status = 400, description = "Invalid name, file types or settings"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 409, description = "A policy with this name exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_compression_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateStorageCompressionPolicyRequest>,
+) -> Result<(StatusCode, Json<StorageCompressionPolicy>), StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected compression policy: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let policy = state
+        .db
+        .create_storage_compression_policy(&request, auth_user.user.id)
+        .await
+        .map_err(map_write_error)?;
+    info!("User {} created compression policy '{}'", auth_user.user.username, policy.name);
+
+    Ok((StatusCode::CREATED, Json(policy)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/storage/compression/policies/{id}",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Policy ID")
+    ),
+    responses(
+        (status = 200, description = "Compression policy", body = StorageCompressionPolicy),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Policy not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_compression_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<StorageCompressionPolicy>, StatusCode> {
+    require_admin(&auth_user)?;
+    let policy = state
+        .db
+        .get_storage_compression_policy(id)
+        .await
+        .map_err(|e| internal_error("Failed to get compression policy", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(policy))
+}
+
+/// Change a compression policy (admin only). Documents already recompressed keep
+/// their file and the window of their original.
+#[utoipa::path(
+    put,
+    path = "/api/storage/compression/policies/{id}",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Policy ID")
+    ),
+    request_body = UpdateStorageCompressionPolicyRequest,
+    responses(
+        (status = 200, description = "Compression policy updated", body = StorageCompressionPolicy),
+        (status = 400, description = "Invalid name, file types or settings"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Policy not found"),
+        (status = 409, description = "A policy with this name exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_compression_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateStorageCompressionPolicyRequest>,
+) -> Result<Json<StorageCompressionPolicy>, StatusCode> {
+    require_admin(&auth_user)?;
+    if let Err(reason) = request.validate() {
+        debug!("Rejected compression policy update: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // A JPEG policy only applies to images, whichever of the two fields changes
+    if let (None, Some(_)) | (Some(_), None) = (&request.mime_types, request.target_format) {
+        let current = state
+            .db
+            .get_storage_compression_policy(id)
+            .await
+            .map_err(|e| internal_error("Failed to get compression policy", e))?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        let merged = CreateStorageCompressionPolicyRequest {
+            name: current.name,
+            enabled: None,
+            mime_types: request.mime_types.clone().unwrap_or(current.mime_types),
+            min_file_size_bytes: None,
+            target_format: request.target_format.unwrap_or(current.target_format),
+            target_dpi: None,
+            jpeg_quality: None,
+            keep_original_days: None,
+        };
+        if let Err(reason) = merged.validate() {
+            debug!("Rejected compression policy update: {}", reason);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let policy = state
+        .db
+        .update_storage_compression_policy(id, &request)
+        .await
+        .map_err(map_write_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(policy))
+}
+
+/// Delete a compression policy (admin only). Originals kept under it stay until their
+/// window ends.
+#[utoipa::path(
+    delete,
+    path = "/api/storage/compression/policies/{id}",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Policy ID")
+    ),
+    responses(
+        (status = 204, description = "Compression policy deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Policy not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_compression_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+    let deleted = state
+        .db
+        .delete_storage_compression_policy(id)
+        .await
+        .map_err(|e| internal_error("Failed to delete compression policy", e))?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    info!("User {} deleted compression policy {}", auth_user.user.username, id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Storage saved by recompression, and taken by the originals still kept (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/storage/compression/summary",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Recompressed documents and their sizes", body = StorageCompressionSummary),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_compression_summary(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<StorageCompressionSummary>, StatusCode> {
+    require_admin(&auth_user)?;
+    let summary = state
+        .db
+        .get_storage_compression_summary()
+        .await
+        .map_err(|e| internal_error("Failed to get compression summary", e))?;
+
+    Ok(Json(summary))
+}
+
+/// Download the original a document was recompressed from, while it is kept
+/// (document owner or admin)
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/original",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The original file", content_type = "application/octet-stream"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found or never recompressed"),
+        (status = 410, description = "The original was deleted when its window ended"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn download_compressed_original(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> Result<Response<Body>, StatusCode> {
+    let document = state
+        .db
+        .get_document_by_id(id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| internal_error("Failed to get document", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let original = state
+        .db
+        .get_compressed_original(document.id)
+        .await
+        .map_err(|e| internal_error("Failed to get original", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let file_path = original.file_path.as_deref().ok_or(StatusCode::GONE)?;
+
+    let data = state
+        .file_service
+        .read_file(file_path)
+        .await
+        .map_err(|e| internal_error("Failed to read original", e))?;
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::DOCUMENT_DOWNLOAD, "document", Some(document.id))
+            .details(serde_json::json!({ "original": true, "filename": original.original_filename })),
+    )
+    .await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, &original.mime_type)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", original.original_filename),
+        )
+        .header(header::CONTENT_LENGTH, data.len().to_string())
+        .body(Body::from(data))
+        .map_err(|e| {
+            error!("Failed to build response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
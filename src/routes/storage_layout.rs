@@ -0,0 +1,258 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{StorageLayout, StorageLayoutUpdate, StorageRelocation, UpdateStorageLayoutRequest},
+    routes::queue::require_admin,
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    services::storage_layout::StoragePathTemplate,
+    services::storage_relocation_service::StorageRelocationService,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(get_storage_layout).put(update_storage_layout))
+        .route("/relocations", get(list_storage_relocations).post(start_storage_relocation))
+        .route("/relocations/{id}", get(get_storage_relocation))
+}
+
+fn spawn_relocation(state: &AppState, relocation_id: Uuid) {
+    let service = StorageRelocationService::new(state.db.clone(), state.file_service.as_ref().clone());
+    spawn_guarded(format!("storage relocation {}", relocation_id), async move {
+        if let Err(e) = service.run(relocation_id).await {
+            error!("Storage relocation {} stopped: {}", relocation_id, e);
+        }
+    });
+}
+
+/// Start moving existing files into the current layout, unless a relocation is running
+async fn start_relocation(state: &AppState, template: Option<&str>, user_id: Uuid) -> Result<StorageRelocation, StatusCode> {
+    let relocation = state.db.create_storage_relocation(template, user_id).await.map_err(|e| {
+        error!("Failed to create storage relocation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    spawn_relocation(state, relocation.id);
+    Ok(relocation)
+}
+
+async fn ensure_no_running_relocation(state: &AppState) -> Result<(), StatusCode> {
+    let running = state.db.get_running_storage_relocation().await.map_err(|e| {
+        error!("Failed to check for running storage relocations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    match running {
+        Some(_) => Err(StatusCode::CONFLICT),
+        None => Ok(()),
+    }
+}
+
+/// The template document files are stored by
+#[utoipa::path(
+    get,
+    path = "/api/storage/layout",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Storage layout; no template means the backend's own layout", body = StorageLayout),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_storage_layout(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<StorageLayout>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let layout = state.db.get_storage_layout().await.map_err(|e| {
+        error!("Failed to get storage layout: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(layout))
+}
+
+/// Change the template document files are stored by
+///
+/// New files are stored by the new template right away. Unless `relocate` is false, a
+/// relocation moves existing files into the new layout in the background.
+#[utoipa::path(
+    put,
+    path = "/api/storage/layout",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = UpdateStorageLayoutRequest,
+    responses(
+        (status = 200, description = "Layout saved, with the relocation it started", body = StorageLayoutUpdate),
+        (status = 400, description = "Invalid template"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 409, description = "A storage relocation is running"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn update_storage_layout(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<UpdateStorageLayoutRequest>,
+) -> Result<Json<StorageLayoutUpdate>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let template = request.template.as_deref().map(str::trim).filter(|template| !template.is_empty());
+    let template = template
+        .map(StoragePathTemplate::parse)
+        .transpose()
+        .map_err(|e| {
+            debug!("Invalid storage layout template: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+    let template = template.as_ref().map(StoragePathTemplate::as_str);
+    ensure_no_running_relocation(&state).await?;
+
+    let previous = state.db.get_storage_layout().await.map_err(|e| {
+        error!("Failed to get storage layout: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let layout = state.db.set_storage_layout(template, auth_user.user.id).await.map_err(|e| {
+        error!("Failed to save storage layout: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::STORAGE_LAYOUT_UPDATE, "storage_layout", None).details(json!({
+            "previous_template": previous.template,
+            "template": layout.template,
+        })),
+    )
+    .await;
+    info!("User {} changed the storage layout to {:?}", auth_user.user.id, layout.template);
+
+    let relocation = if request.relocate.unwrap_or(true) && previous.template != layout.template {
+        Some(start_relocation(&state, template, auth_user.user.id).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(StorageLayoutUpdate { layout, relocation }))
+}
+
+/// Relocations, newest first
+#[utoipa::path(
+    get,
+    path = "/api/storage/layout/relocations",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Storage relocations with their progress", body = Vec<StorageRelocation>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_storage_relocations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<StorageRelocation>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let relocations = state.db.list_storage_relocations().await.map_err(|e| {
+        error!("Failed to list storage relocations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(relocations))
+}
+
+/// Move files that are not where the current layout puts them, e.g. after correspondents
+/// or document types were assigned, or to retry failed documents
+#[utoipa::path(
+    post,
+    path = "/api/storage/layout/relocations",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 202, description = "Relocation started in the background", body = StorageRelocation),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 409, description = "A storage relocation is running"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn start_storage_relocation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<(StatusCode, Json<StorageRelocation>), StatusCode> {
+    require_admin(&auth_user)?;
+    ensure_no_running_relocation(&state).await?;
+
+    let layout = state.db.get_storage_layout().await.map_err(|e| {
+        error!("Failed to get storage layout: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let relocation = start_relocation(&state, layout.template.as_deref(), auth_user.user.id).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(relocation)))
+}
+
+/// Progress of a relocation and the documents it could not move
+#[utoipa::path(
+    get,
+    path = "/api/storage/layout/relocations/{id}",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Storage relocation ID")
+    ),
+    responses(
+        (status = 200, description = "Storage relocation", body = StorageRelocation),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Storage relocation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_storage_relocation(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<StorageRelocation>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    state
+        .db
+        .get_storage_relocation(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get storage relocation {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
@@ -0,0 +1,211 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    errors::panic::spawn_guarded,
+    models::{StartStorageMigrationRequest, StorageMigration, StorageMigrationStatus},
+    routes::queue::require_admin,
+    services::storage_migration_service::StorageMigrationService,
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_storage_migrations).post(start_storage_migration))
+        .route("/{id}", get(get_storage_migration))
+        .route("/{id}/resume", post(resume_storage_migration))
+}
+
+fn migration_service(state: &AppState) -> StorageMigrationService {
+    StorageMigrationService::new(state.db.clone(), state.file_service.as_ref().clone(), state.config.clone())
+}
+
+fn spawn_migration(state: &AppState, migration_id: Uuid) {
+    let service = migration_service(state);
+    spawn_guarded(format!("storage migration {}", migration_id), async move {
+        if let Err(e) = service.run(migration_id).await {
+            error!("Storage migration {} stopped: {}", migration_id, e);
+        }
+    });
+}
+
+/// Storage migrations, newest first
+#[utoipa::path(
+    get,
+    path = "/api/storage/migrations",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Storage migrations with their progress and reports", body = Vec<StorageMigration>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn list_storage_migrations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<StorageMigration>>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let migrations = state.db.list_storage_migrations().await.map_err(|e| {
+        error!("Failed to list storage migrations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(migrations))
+}
+
+/// Start moving document files into another backend or into the target backend's layout
+#[utoipa::path(
+    post,
+    path = "/api/storage/migrations",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = StartStorageMigrationRequest,
+    responses(
+        (status = 202, description = "Migration started in the background", body = StorageMigration),
+        (status = 400, description = "Target backend is not configured"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 409, description = "Another storage migration is running"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn start_storage_migration(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<StartStorageMigrationRequest>,
+) -> Result<(StatusCode, Json<StorageMigration>), StatusCode> {
+    require_admin(&auth_user)?;
+
+    let running = state.db.get_running_storage_migration().await.map_err(|e| {
+        error!("Failed to check for running storage migrations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if running.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let service = migration_service(&state);
+    if let Err(e) = service.target_backend(request.target).await {
+        error!("Storage migration target {} is unavailable: {}", request.target, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let migration = state
+        .db
+        .create_storage_migration(
+            request.target,
+            StorageMigrationService::batch_size(request.batch_size),
+            request.delete_source,
+            auth_user.user.id,
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create storage migration: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    spawn_migration(&state, migration.id);
+    Ok((StatusCode::ACCEPTED, Json(migration)))
+}
+
+/// Progress, or the reconciliation report once finished
+#[utoipa::path(
+    get,
+    path = "/api/storage/migrations/{id}",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Storage migration ID")
+    ),
+    responses(
+        (status = 200, description = "Storage migration", body = StorageMigration),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Storage migration not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_storage_migration(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<StorageMigration>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    state
+        .db
+        .get_storage_migration(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get storage migration {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Continue a failed or interrupted migration from the last completed batch
+#[utoipa::path(
+    post,
+    path = "/api/storage/migrations/{id}/resume",
+    tag = "storage",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Storage migration ID")
+    ),
+    responses(
+        (status = 202, description = "Migration resumed in the background", body = StorageMigration),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Storage migration not found"),
+        (status = 409, description = "Migration already completed, or another migration is running"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn resume_storage_migration(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<StorageMigration>), StatusCode> {
+    require_admin(&auth_user)?;
+
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to resume storage migration {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let migration = state.db.get_storage_migration(id).await.map_err(db_error)?.ok_or(StatusCode::NOT_FOUND)?;
+    let migration = match migration.status {
+        StorageMigrationStatus::Completed => return Err(StatusCode::CONFLICT),
+        StorageMigrationStatus::Running => migration,
+        StorageMigrationStatus::Failed => {
+            if state.db.get_running_storage_migration().await.map_err(db_error)?.is_some() {
+                return Err(StatusCode::CONFLICT);
+            }
+            state.db.reopen_storage_migration(id).await.map_err(db_error)?
+        }
+    };
+
+    spawn_migration(&state, migration.id);
+    Ok((StatusCode::ACCEPTED, Json(migration)))
+}
@@ -0,0 +1,170 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{SetStorageQuotaRequest, StorageUsage},
+    routes::queue::require_admin,
+    services::storage_quota_service::{mb_to_bytes, StorageQuotaService},
+    AppState,
+};
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+async fn require_user(state: &AppState, user_id: Uuid) -> Result<(), StatusCode> {
+    state
+        .db
+        .get_user_by_id(user_id)
+        .await
+        .map_err(|e| internal_error("Failed to get user", e))?
+        .map(|_| ())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/me/usage",
+    tag = "users",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Storage used by the current user and their quota", body = StorageUsage),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_my_usage(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<StorageUsage>, StatusCode> {
+    let usage = StorageQuotaService::new(state.db.clone())
+        .usage(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to get storage usage", e))?;
+
+    Ok(Json(usage))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/usage",
+    tag = "users",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Storage used by the user and their quota", body = StorageUsage),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_user_usage(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<StorageUsage>, StatusCode> {
+    require_admin(&auth_user)?;
+    require_user(&state, id).await?;
+
+    let usage = StorageQuotaService::new(state.db.clone())
+        .usage(id)
+        .await
+        .map_err(|e| internal_error("Failed to get storage usage", e))?;
+
+    Ok(Json(usage))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/users/{id}/quota",
+    tag = "users",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    request_body = SetStorageQuotaRequest,
+    responses(
+        (status = 200, description = "Quota overridden; the user's usage against it", body = StorageUsage),
+        (status = 400, description = "Negative quota"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn set_user_quota(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SetStorageQuotaRequest>,
+) -> Result<Json<StorageUsage>, StatusCode> {
+    require_admin(&auth_user)?;
+    if request.quota_mb.is_some_and(|mb| mb < 0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    require_user(&state, id).await?;
+
+    state
+        .db
+        .set_user_storage_quota(id, request.quota_mb.map(mb_to_bytes), auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to set storage quota", e))?;
+    info!("User {} set the storage quota of {} to {:?} MB", auth_user.user.id, id, request.quota_mb);
+
+    get_user_usage(State(state), auth_user, Path(id)).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/users/{id}/quota",
+    tag = "users",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "Override removed; the default quota applies again"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "The user has no quota override"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_user_quota(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let deleted = state
+        .db
+        .delete_user_storage_quota(id)
+        .await
+        .map_err(|e| internal_error("Failed to delete storage quota", e))?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("User {} removed the storage quota override of {}", auth_user.user.id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,116 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{TimelineEvent, TimelineQuery, UserRole, DEFAULT_TIMELINE_EVENTS, MAX_TIMELINE_EVENTS},
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/", get(list_timeline))
+}
+
+/// Dated events extracted from the user's documents, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/timeline",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(TimelineQuery),
+    responses(
+        (status = 200, description = "Events from the latest analysis of each document", body = Vec<TimelineEvent>),
+        (status = 400, description = "The date range ends before it starts"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Entity not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_timeline(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<TimelineQuery>,
+) -> Result<Json<Vec<TimelineEvent>>, StatusCode> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if to < from {
+            debug!("Invalid timeline range: {} to {}", from, to);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to list timeline events: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    // An entity's timeline covers its owner's documents, which only admins may see for
+    // someone else's entity
+    let entity = match query.entity_id {
+        Some(entity_id) => {
+            let entity = state.db.get_graph_entity(entity_id).await.map_err(db_error)?.ok_or(StatusCode::NOT_FOUND)?;
+            if entity.user_id != auth_user.user.id && auth_user.user.role != UserRole::Admin {
+                return Err(StatusCode::NOT_FOUND);
+            }
+            Some(entity)
+        }
+        None => None,
+    };
+    let owner = entity.as_ref().map_or(auth_user.user.id, |entity| entity.user_id);
+    let limit = query.limit.unwrap_or(DEFAULT_TIMELINE_EVENTS).clamp(1, MAX_TIMELINE_EVENTS);
+
+    let events = state
+        .db
+        .list_timeline_events(owner, entity.as_ref(), query.from, query.to, limit)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(events))
+}
+
+/// Dated events extracted from the document by its latest analysis, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/documents/{id}/timeline",
+    tag = "analysis",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "The document's events; empty until it is analyzed", body = Vec<TimelineEvent>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Document not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_document_timeline(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<TimelineEvent>>, StatusCode> {
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to get timeline of document {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    state
+        .db
+        .get_document_by_id(id, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(db_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let events = state.db.get_document_timeline(id).await.map_err(db_error)?;
+    Ok(Json(events))
+}
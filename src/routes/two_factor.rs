@@ -0,0 +1,617 @@
+use axum::{
+    extract::{FromRequestParts, Path, State},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        extract_token_from_headers, start_session, verify_two_factor_challenge, AuthUser, CHALLENGE_LOGIN,
+        CHALLENGE_SETUP,
+    },
+    models::{
+        AuthPolicy, DisableTwoFactorRequest, EnableTwoFactorResponse, KeyOwner, LoginResponse, RecoveryCodesResponse,
+        TwoFactorCodeRequest, TwoFactorLoginRequest, TwoFactorSetupResponse, TwoFactorStatus,
+        UpdateAuthPolicyRequest, User,
+    },
+    routes::queue::require_admin,
+    services::{
+        audit_service::{self, AuditEvent, ClientInfo},
+        two_factor_service,
+    },
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(get_two_factor_status))
+        .route("/setup", post(setup_two_factor))
+        .route("/enable", post(enable_two_factor))
+        .route("/disable", post(disable_two_factor))
+        .route("/recovery-codes", post(regenerate_recovery_codes))
+        .route("/policy", get(get_auth_policy).put(update_auth_policy))
+}
+
+/// The user enrolling in two-factor login: a signed in user, or one whose login is
+/// waiting for enrollment because the server requires it
+pub struct TwoFactorEnrollee {
+    pub user: User,
+    pub via_challenge: bool,
+}
+
+impl FromRequestParts<Arc<AppState>> for TwoFactorEnrollee {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let challenge_user = extract_token_from_headers(&parts.headers)
+            .and_then(|token| verify_two_factor_challenge(&token, CHALLENGE_SETUP, &state.config.jwt_secret).ok());
+
+        if let Some(user_id) = challenge_user {
+            let user = state
+                .db
+                .get_user_by_id(user_id)
+                .await
+                .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())?
+                .ok_or_else(|| (StatusCode::UNAUTHORIZED, "User not found").into_response())?;
+            return Ok(TwoFactorEnrollee { user, via_challenge: true });
+        }
+
+        let auth_user = AuthUser::from_request_parts(parts, state).await?;
+        Ok(TwoFactorEnrollee { user: auth_user.user, via_challenge: false })
+    }
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// TOTP secrets are sealed with the user's key when encryption is enabled
+async fn seal_secret(state: &AppState, user_id: Uuid, secret: &str) -> anyhow::Result<String> {
+    match state.file_service.encryption() {
+        Some(encryption) => encryption.encrypt_field(KeyOwner::User(user_id), secret).await,
+        None => Ok(secret.to_string()),
+    }
+}
+
+async fn open_secret(state: &AppState, secret: &str) -> anyhow::Result<String> {
+    match state.file_service.encryption() {
+        Some(encryption) => encryption.decrypt_field(secret).await,
+        None => Ok(secret.to_string()),
+    }
+}
+
+/// Recovery codes for the user to keep, with only their hashes stored
+fn new_recovery_codes() -> (Vec<String>, Vec<String>) {
+    let codes = two_factor_service::generate_recovery_codes();
+    let hashes = codes.iter().map(|code| two_factor_service::hash_recovery_code(code)).collect();
+    (codes, hashes)
+}
+
+/// Check a TOTP or recovery code against the user's enabled second factor. Wrong codes
+/// count towards a temporary lockout, which answers 429.
+pub(crate) async fn verify_second_factor(state: &AppState, user_id: Uuid, code: &str) -> Result<&'static str, StatusCode> {
+    let two_factor = state
+        .db
+        .get_user_two_factor(user_id)
+        .await
+        .map_err(|e| internal_error("Failed to load second factor", e))?
+        .filter(|two_factor| two_factor.is_enabled())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    if two_factor.is_locked(Utc::now()) {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let accepted = if two_factor_service::is_totp_code(code) {
+        let secret = open_secret(state, &two_factor.secret)
+            .await
+            .map_err(|e| internal_error("Failed to open TOTP secret", e))?;
+        match two_factor_service::verify_code(&secret, code, Utc::now().timestamp(), two_factor.last_used_step) {
+            Some(step) => state
+                .db
+                .record_two_factor_success(user_id, Some(step))
+                .await
+                .map_err(|e| internal_error("Failed to record second factor use", e))?
+                .then_some("totp"),
+            None => None,
+        }
+    } else {
+        let used = state
+            .db
+            .use_recovery_code(user_id, &two_factor_service::hash_recovery_code(code))
+            .await
+            .map_err(|e| internal_error("Failed to use recovery code", e))?;
+        if used {
+            state
+                .db
+                .record_two_factor_success(user_id, None)
+                .await
+                .map_err(|e| internal_error("Failed to record second factor use", e))?;
+        }
+        used.then_some("recovery_code")
+    };
+
+    match accepted {
+        Some(method) => Ok(method),
+        None => {
+            state
+                .db
+                .record_two_factor_failure(user_id)
+                .await
+                .map_err(|e| internal_error("Failed to record second factor failure", e))?;
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+/// Two-factor status of the current user
+#[utoipa::path(
+    get,
+    path = "/api/auth/2fa",
+    tag = "two_factor",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Two-factor status", body = TwoFactorStatus),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_two_factor_status(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<TwoFactorStatus>, StatusCode> {
+    let user_id = auth_user.user.id;
+    let two_factor = state
+        .db
+        .get_user_two_factor(user_id)
+        .await
+        .map_err(|e| internal_error("Failed to load second factor", e))?
+        .filter(|two_factor| two_factor.is_enabled());
+    let recovery_codes_remaining = state
+        .db
+        .count_unused_recovery_codes(user_id)
+        .await
+        .map_err(|e| internal_error("Failed to count recovery codes", e))?;
+    let policy = state
+        .db
+        .get_auth_policy()
+        .await
+        .map_err(|e| internal_error("Failed to load auth policy", e))?;
+
+    Ok(Json(TwoFactorStatus {
+        enabled: two_factor.is_some(),
+        enabled_at: two_factor.and_then(|two_factor| two_factor.enabled_at),
+        recovery_codes_remaining,
+        required: policy.require_two_factor && auth_user.user.password_hash.is_some(),
+    }))
+}
+
+/// Start enrolling: a new secret to add to an authenticator app
+///
+/// Accepts a session token, or the challenge token of a login that requires enrollment.
+/// Nothing changes until the first code is confirmed with `/api/auth/2fa/enable`.
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/setup",
+    tag = "two_factor",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Secret and provisioning URI", body = TwoFactorSetupResponse),
+        (status = 400, description = "Not a local account"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Two-factor login is already enabled"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn setup_two_factor(
+    State(state): State<Arc<AppState>>,
+    enrollee: TwoFactorEnrollee,
+) -> Result<Json<TwoFactorSetupResponse>, StatusCode> {
+    let user = &enrollee.user;
+    // OIDC accounts get their second factor from the identity provider
+    if user.password_hash.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let secret = two_factor_service::generate_secret();
+    let sealed = seal_secret(&state, user.id, &secret)
+        .await
+        .map_err(|e| internal_error("Failed to seal TOTP secret", e))?;
+    let started = state
+        .db
+        .begin_two_factor_setup(user.id, &sealed)
+        .await
+        .map_err(|e| internal_error("Failed to start two-factor setup", e))?;
+    if !started {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    Ok(Json(TwoFactorSetupResponse {
+        otpauth_uri: two_factor_service::provisioning_uri(&user.username, &secret),
+        secret,
+    }))
+}
+
+/// Finish enrolling with a code from the authenticator app
+///
+/// Returns the recovery codes, and a session when enrollment completes a login.
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/enable",
+    tag = "two_factor",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "Two-factor login enabled", body = EnableTwoFactorResponse),
+        (status = 400, description = "Setup was not started"),
+        (status = 401, description = "Wrong code"),
+        (status = 409, description = "Two-factor login is already enabled"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn enable_two_factor(
+    State(state): State<Arc<AppState>>,
+    enrollee: TwoFactorEnrollee,
+    client: ClientInfo,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<EnableTwoFactorResponse>, StatusCode> {
+    let user = enrollee.user;
+    let two_factor = state
+        .db
+        .get_user_two_factor(user.id)
+        .await
+        .map_err(|e| internal_error("Failed to load second factor", e))?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    if two_factor.is_enabled() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let secret = open_secret(&state, &two_factor.secret)
+        .await
+        .map_err(|e| internal_error("Failed to open TOTP secret", e))?;
+    let step = two_factor_service::verify_code(&secret, &request.code, Utc::now().timestamp(), None)
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let (recovery_codes, hashes) = new_recovery_codes();
+    state
+        .db
+        .enable_two_factor(user.id, step, &hashes)
+        .await
+        .map_err(|e| internal_error("Failed to enable two-factor login", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(&user),
+        &client,
+        AuditEvent::new(audit_service::TWO_FACTOR_ENABLE, "user", Some(user.id)),
+    )
+    .await;
+    info!("User {} enabled two-factor login", user.username);
+
+    // Enrollment was the last step of a login the policy held back
+    let (token, user_response) = if enrollee.via_challenge {
+        let token = start_session(&state, &user, "password+totp", &client)
+            .await
+            .map_err(|e| internal_error("Failed to start session", e))?;
+        audit_service::record(
+            &state.db,
+            Some(&user),
+            &client,
+            AuditEvent::new(audit_service::LOGIN, "user", Some(user.id))
+                .details(serde_json::json!({ "method": "password+totp_enrollment" })),
+        )
+        .await;
+        (Some(token), Some(user.into()))
+    } else {
+        (None, None)
+    };
+
+    Ok(Json(EnableTwoFactorResponse {
+        recovery_codes,
+        token,
+        user: user_response,
+    }))
+}
+
+/// Turn off two-factor login for the current user
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/disable",
+    tag = "two_factor",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = DisableTwoFactorRequest,
+    responses(
+        (status = 204, description = "Two-factor login disabled"),
+        (status = 400, description = "Two-factor login is not enabled"),
+        (status = 401, description = "Wrong password or code"),
+        (status = 403, description = "The server requires two-factor login"),
+        (status = 429, description = "Too many wrong codes, try again later"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn disable_two_factor(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<DisableTwoFactorRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let user = &auth_user.user;
+    let password_hash = user.password_hash.as_deref().ok_or(StatusCode::BAD_REQUEST)?;
+    if !bcrypt::verify(&request.password, password_hash).unwrap_or(false) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let policy = state
+        .db
+        .get_auth_policy()
+        .await
+        .map_err(|e| internal_error("Failed to load auth policy", e))?;
+    if policy.require_two_factor {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    verify_second_factor(&state, user.id, &request.code).await?;
+    state
+        .db
+        .disable_two_factor(user.id)
+        .await
+        .map_err(|e| internal_error("Failed to disable two-factor login", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(user),
+        &client,
+        AuditEvent::new(audit_service::TWO_FACTOR_DISABLE, "user", Some(user.id)),
+    )
+    .await;
+    info!("User {} disabled two-factor login", user.username);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replace the current user's recovery codes
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/recovery-codes",
+    tag = "two_factor",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "New recovery codes; the old ones stop working", body = RecoveryCodesResponse),
+        (status = 400, description = "Two-factor login is not enabled"),
+        (status = 401, description = "Wrong code"),
+        (status = 429, description = "Too many wrong codes, try again later"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, StatusCode> {
+    let user_id = auth_user.user.id;
+    verify_second_factor(&state, user_id, &request.code).await?;
+
+    let (recovery_codes, hashes) = new_recovery_codes();
+    state
+        .db
+        .replace_recovery_codes(user_id, &hashes)
+        .await
+        .map_err(|e| internal_error("Failed to replace recovery codes", e))?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Second step of a login: exchange the challenge token and a code for a session
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/2fa",
+    tag = "auth",
+    request_body = TwoFactorLoginRequest,
+    responses(
+        (status = 200, description = "Login successful", body = LoginResponse),
+        (status = 401, description = "Invalid or expired challenge, or wrong code"),
+        (status = 429, description = "Too many wrong codes, try again later"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn login_second_factor(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(request): Json<TwoFactorLoginRequest>,
+) -> Result<Json<LoginResponse>, StatusCode> {
+    let user_id = verify_two_factor_challenge(&request.challenge_token, CHALLENGE_LOGIN, &state.config.jwt_secret)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let user = state
+        .db
+        .get_user_by_id(user_id)
+        .await
+        .map_err(|e| internal_error("Failed to load user", e))?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let method = match verify_second_factor(&state, user.id, &request.code).await {
+        Ok(method) => method,
+        Err(status) => {
+            let reason = match status {
+                StatusCode::UNAUTHORIZED => Some("invalid_second_factor"),
+                StatusCode::TOO_MANY_REQUESTS => Some("second_factor_locked"),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                audit_service::record(
+                    &state.db,
+                    None,
+                    &client,
+                    AuditEvent::new(audit_service::LOGIN_FAILED, "user", Some(user.id))
+                        .details(serde_json::json!({ "username": user.username, "reason": reason })),
+                )
+                .await;
+            }
+            return Err(status);
+        }
+    };
+
+    let method = format!("password+{}", method);
+    let token = start_session(&state, &user, &method, &client)
+        .await
+        .map_err(|e| internal_error("Failed to start session", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(&user),
+        &client,
+        AuditEvent::new(audit_service::LOGIN, "user", Some(user.id))
+            .details(serde_json::json!({ "method": method })),
+    )
+    .await;
+
+    Ok(Json(LoginResponse {
+        token,
+        user: user.into(),
+    }))
+}
+
+/// Remove another user's second factor, e.g. after they lost their device (admin only).
+/// If the server requires two-factor login they enroll again at their next login.
+#[utoipa::path(
+    delete,
+    path = "/api/users/{id}/2fa",
+    tag = "two_factor",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "Second factor removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "The user has no second factor"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn reset_user_two_factor(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let removed = state
+        .db
+        .disable_two_factor(user_id)
+        .await
+        .map_err(|e| internal_error("Failed to reset two-factor login", e))?;
+    if !removed {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::TWO_FACTOR_DISABLE, "user", Some(user_id))
+            .details(serde_json::json!({ "reset_by_admin": true })),
+    )
+    .await;
+    info!("Admin {} reset two-factor login of user {}", auth_user.user.id, user_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/2fa/policy",
+    tag = "two_factor",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Login policy", body = AuthPolicy),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_auth_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<AuthPolicy>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let policy = state
+        .db
+        .get_auth_policy()
+        .await
+        .map_err(|e| internal_error("Failed to load auth policy", e))?;
+    Ok(Json(policy))
+}
+
+/// Require two-factor login for every local account (admin only)
+///
+/// Users without a second factor are asked to enroll at their next login; OIDC accounts
+/// are not affected.
+#[utoipa::path(
+    put,
+    path = "/api/auth/2fa/policy",
+    tag = "two_factor",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = UpdateAuthPolicyRequest,
+    responses(
+        (status = 200, description = "Policy updated", body = AuthPolicy),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_auth_policy(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<UpdateAuthPolicyRequest>,
+) -> Result<Json<AuthPolicy>, StatusCode> {
+    require_admin(&auth_user)?;
+
+    let before = state
+        .db
+        .get_auth_policy()
+        .await
+        .map_err(|e| internal_error("Failed to load auth policy", e))?;
+    let policy = state
+        .db
+        .update_auth_policy(request.require_two_factor, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to update auth policy", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::AUTH_POLICY_UPDATE, "settings", None).changes(
+            &serde_json::json!({ "require_two_factor": before.require_two_factor }),
+            &serde_json::json!({ "require_two_factor": policy.require_two_factor }),
+        ),
+    )
+    .await;
+
+    Ok(Json(policy))
+}
@@ -0,0 +1,459 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{ResumableUpload, ResumableUploadStatus},
+    routes::documents::crud::{ingest_upload, UploadedFile},
+    services::audit_service::ClientInfo,
+    services::storage_quota_service::StorageQuotaService,
+    services::upload_service::{
+        self, AppendError, Checksum, UploadService, TUS_CHECKSUM_ALGORITHMS, TUS_EXTENSIONS, TUS_VERSION,
+    },
+    utils::security::validate_filename,
+    AppState,
+};
+
+/// Content type of tus PATCH requests
+const OFFSET_OCTET_STREAM: &str = "application/offset+octet-stream";
+/// Response header naming the document a finished upload became
+const DOCUMENT_ID_HEADER: &str = "Readur-Document-Id";
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", post(create_upload).options(get_upload_options))
+        .route(
+            "/{id}",
+            get(get_upload)
+                .head(get_upload_offset)
+                .patch(append_upload)
+                .delete(terminate_upload),
+        )
+}
+
+fn tus_response(status: StatusCode) -> axum::http::response::Builder {
+    Response::builder()
+        .status(status)
+        .header("Tus-Resumable", TUS_VERSION)
+}
+
+fn build(builder: axum::http::response::Builder) -> Result<Response, StatusCode> {
+    builder.body(Body::empty()).map_err(|e| {
+        error!("Failed to build response: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Every request but OPTIONS names the protocol version it speaks
+fn require_tus_version(headers: &HeaderMap) -> Result<(), StatusCode> {
+    match headers.get("Tus-Resumable").and_then(|v| v.to_str().ok()) {
+        Some(TUS_VERSION) => Ok(()),
+        _ => Err(StatusCode::PRECONDITION_FAILED),
+    }
+}
+
+fn header_i64(headers: &HeaderMap, name: &str) -> Option<i64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn max_upload_size(state: &AppState) -> i64 {
+    state.config.max_file_size_mb as i64 * 1024 * 1024
+}
+
+async fn find_upload(state: &AppState, id: Uuid, auth_user: &AuthUser) -> Result<ResumableUpload, StatusCode> {
+    state
+        .db
+        .get_resumable_upload(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get upload {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Protocol version, extensions and limits of the server
+#[utoipa::path(
+    options,
+    path = "/api/uploads",
+    tag = "documents",
+    responses(
+        (status = 204, description = "Supported tus version, extensions, checksum algorithms and maximum size in the Tus-* headers")
+    )
+)]
+pub async fn get_upload_options(State(state): State<Arc<AppState>>) -> Result<Response, StatusCode> {
+    build(
+        tus_response(StatusCode::NO_CONTENT)
+            .header("Tus-Version", TUS_VERSION)
+            .header("Tus-Extension", TUS_EXTENSIONS)
+            .header("Tus-Checksum-Algorithm", TUS_CHECKSUM_ALGORITHMS)
+            .header("Tus-Max-Size", max_upload_size(&state).to_string()),
+    )
+}
+
+/// Start a resumable upload. `Upload-Length` is the size of the file and
+/// `Upload-Metadata` carries base64 `filename`, and optionally `filetype` and `ocr_language`.
+#[utoipa::path(
+    post,
+    path = "/api/uploads",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("Tus-Resumable" = String, Header, description = "Protocol version, 1.0.0"),
+        ("Upload-Length" = i64, Header, description = "Size of the file in bytes"),
+        ("Upload-Metadata" = String, Header, description = "Comma-separated `key base64value` pairs; `filename` is required")
+    ),
+    responses(
+        (status = 201, description = "Upload created; send the file to the URL in Location"),
+        (status = 400, description = "Missing length or filename, or invalid metadata"),
+        (status = 401, description = "Unauthorized"),
+        (status = 412, description = "Unsupported tus version"),
+        (status = 413, description = "File larger than the maximum upload size, or it does not fit in the user's storage quota"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_upload(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    require_tus_version(&headers)?;
+
+    let upload_length = header_i64(&headers, "Upload-Length")
+        .filter(|length| *length > 0)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    if upload_length > max_upload_size(&state) {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let metadata = headers
+        .get("Upload-Metadata")
+        .and_then(|v| v.to_str().ok())
+        .map(upload_service::parse_metadata)
+        .transpose()
+        .map_err(|e| {
+            warn!("Invalid upload metadata: {}", e);
+            StatusCode::BAD_REQUEST
+        })?
+        .unwrap_or_default();
+
+    let filename = metadata
+        .get("filename")
+        .and_then(|filename| validate_filename(filename).ok())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    // Browsers rarely send a usable type for .eml/.msg files
+    let mime_type = crate::ocr::email_parser::email_mime_type(&filename)
+        .or(metadata.get("filetype").map(String::as_str).filter(|filetype| !filetype.is_empty()))
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let ocr_language = metadata
+        .get("ocr_language")
+        .map(|language| language.trim())
+        .filter(|language| !language.is_empty());
+    if let Some(language) = ocr_language {
+        if crate::ocr::health::OcrHealthChecker::new().validate_language(language).is_err() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    // Refuse early rather than after the whole file was sent
+    let exceeded = StorageQuotaService::new(state.db.clone())
+        .check(auth_user.user.id, &filename, upload_length)
+        .await
+        .map_err(|e| {
+            error!("Failed to check storage quota: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if let Some(exceeded) = exceeded {
+        info!("Refusing upload by user {}: {}", auth_user.user.id, exceeded);
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let upload = state
+        .db
+        .create_resumable_upload(
+            auth_user.user.id,
+            &filename,
+            &mime_type,
+            upload_length,
+            ocr_language,
+            Utc::now() + upload_service::expiration(),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to create upload: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let service = UploadService::new(state.clone());
+    if let Err(e) = service.create_part_file(upload.id).await {
+        error!("Failed to create part file of upload {}: {}", upload.id, e);
+        if let Err(e) = service.discard(upload.id).await {
+            warn!("Failed to remove upload {}: {}", upload.id, e);
+        }
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    info!("User {} started upload {} of {} ({} bytes)", auth_user.user.id, upload.id, filename, upload_length);
+    build(
+        tus_response(StatusCode::CREATED)
+            .header(header::LOCATION, format!("/api/uploads/{}", upload.id))
+            .header("Upload-Expires", http_date(upload.expires_at)),
+    )
+}
+
+/// How many bytes of the upload the server has
+#[utoipa::path(
+    head,
+    path = "/api/uploads/{id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Upload ID"),
+        ("Tus-Resumable" = String, Header, description = "Protocol version, 1.0.0")
+    ),
+    responses(
+        (status = 200, description = "Bytes received in Upload-Offset and file size in Upload-Length"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Upload not found or expired"),
+        (status = 412, description = "Unsupported tus version")
+    )
+)]
+pub async fn get_upload_offset(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    require_tus_version(&headers)?;
+    let upload = find_upload(&state, id, &auth_user).await?;
+
+    let mut response = tus_response(StatusCode::OK)
+        .header(header::CACHE_CONTROL, "no-store")
+        .header("Upload-Offset", upload.upload_offset.to_string())
+        .header("Upload-Length", upload.upload_length.to_string())
+        .header("Upload-Expires", http_date(upload.expires_at));
+    if let Some(document_id) = upload.document_id {
+        response = response.header(DOCUMENT_ID_HEADER, document_id.to_string());
+    }
+    build(response)
+}
+
+/// An upload with its progress, and the document it became once finished
+#[utoipa::path(
+    get,
+    path = "/api/uploads/{id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Upload ID")
+    ),
+    responses(
+        (status = 200, description = "Upload progress", body = ResumableUpload),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Upload not found or expired"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_upload(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ResumableUpload>, StatusCode> {
+    find_upload(&state, id, &auth_user).await.map(Json)
+}
+
+/// Send the next chunk of an upload, starting at `Upload-Offset`. With
+/// `Upload-Checksum` the chunk is kept only if it matches. The file is ingested
+/// like a direct upload once its last byte arrives.
+#[utoipa::path(
+    patch,
+    path = "/api/uploads/{id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Upload ID"),
+        ("Tus-Resumable" = String, Header, description = "Protocol version, 1.0.0"),
+        ("Upload-Offset" = i64, Header, description = "Bytes the server has, from HEAD"),
+        ("Upload-Checksum" = Option<String>, Header, description = "`sha1` or `sha256` and the base64 digest of the chunk")
+    ),
+    request_body(content = String, description = "Chunk of the file", content_type = "application/offset+octet-stream"),
+    responses(
+        (status = 204, description = "Chunk stored; the new offset is in Upload-Offset, and the document in Readur-Document-Id once finished"),
+        (status = 400, description = "Missing offset, invalid checksum header or interrupted chunk"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Upload not found"),
+        (status = 409, description = "Upload-Offset does not match, or the upload is finished"),
+        (status = 410, description = "Upload expired"),
+        (status = 412, description = "Unsupported tus version"),
+        (status = 413, description = "Chunk goes past Upload-Length"),
+        (status = 415, description = "Content-Type is not application/offset+octet-stream"),
+        (status = 423, description = "Another chunk of the upload is being written"),
+        (status = 460, description = "Chunk does not match Upload-Checksum"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn append_upload(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, Response> {
+    require_tus_version(&headers).map_err(IntoResponse::into_response)?;
+    if headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) != Some(OFFSET_OCTET_STREAM) {
+        return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response());
+    }
+    let offset = header_i64(&headers, "Upload-Offset").ok_or_else(|| StatusCode::BAD_REQUEST.into_response())?;
+    let checksum = headers
+        .get("Upload-Checksum")
+        .map(|v| Checksum::parse(v.to_str().unwrap_or_default()))
+        .transpose()
+        .map_err(|e| {
+            warn!("Invalid upload checksum: {}", e);
+            StatusCode::BAD_REQUEST.into_response()
+        })?;
+
+    let upload = find_upload(&state, id, &auth_user).await.map_err(IntoResponse::into_response)?;
+    if upload.status != ResumableUploadStatus::Uploading {
+        return Err(StatusCode::CONFLICT.into_response());
+    }
+    if upload.expires_at < Utc::now() {
+        return Err(StatusCode::GONE.into_response());
+    }
+    let Some(_lock) = upload_service::lock_upload(id) else {
+        return Err(StatusCode::LOCKED.into_response());
+    };
+
+    let service = UploadService::new(state.clone());
+    let upload = service
+        .append(&upload, offset, body.into_data_stream(), checksum)
+        .await
+        .map_err(|e| {
+            let status = match e {
+                AppendError::OffsetMismatch => StatusCode::CONFLICT,
+                AppendError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+                AppendError::ChecksumMismatch => {
+                    StatusCode::from_u16(460).unwrap_or(StatusCode::BAD_REQUEST)
+                }
+                AppendError::Interrupted(reason) => {
+                    info!("Chunk of upload {} was interrupted: {}", id, reason);
+                    StatusCode::BAD_REQUEST
+                }
+                AppendError::Internal(e) => {
+                    error!("Failed to store chunk of upload {}: {}", id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            };
+            status.into_response()
+        })?;
+
+    let mut response = tus_response(StatusCode::NO_CONTENT)
+        .header("Upload-Offset", upload.upload_offset.to_string())
+        .header("Upload-Expires", http_date(upload.expires_at));
+    if upload.upload_offset == upload.upload_length {
+        let document_id = finish_upload(&state, &service, &auth_user, &client, upload).await?;
+        response = response.header(DOCUMENT_ID_HEADER, document_id.to_string());
+    }
+
+    build(response).map_err(IntoResponse::into_response)
+}
+
+/// Ingest a fully received upload and remove its part file
+async fn finish_upload(
+    state: &Arc<AppState>,
+    service: &UploadService,
+    auth_user: &AuthUser,
+    client: &ClientInfo,
+    upload: ResumableUpload,
+) -> Result<Uuid, Response> {
+    let data = tokio::fs::read(service.part_path(upload.id)).await.map_err(|e| {
+        error!("Failed to read finished upload {}: {}", upload.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    let file = UploadedFile { filename: upload.filename.clone(), content_type: upload.mime_type.clone(), data };
+
+    let result = ingest_upload(state, auth_user, client, file, upload.ocr_language.clone(), Vec::new()).await;
+    service.remove_part_file(upload.id).await;
+
+    match result {
+        Ok(uploaded) => {
+            if let Err(e) = state.db.complete_resumable_upload(upload.id, uploaded.id).await {
+                error!("Failed to record finished upload {}: {}", upload.id, e);
+            }
+            info!("Upload {} became document {} ({})", upload.id, uploaded.id, uploaded.status);
+            Ok(uploaded.id)
+        }
+        Err(e) => {
+            let error = format!("{:?}", e);
+            if let Err(db_error) = state.db.fail_resumable_upload(upload.id, &error).await {
+                error!("Failed to record failed upload {}: {}", upload.id, db_error);
+            }
+            Err(e.into_response())
+        }
+    }
+}
+
+/// Cancel an upload and remove what was received
+#[utoipa::path(
+    delete,
+    path = "/api/uploads/{id}",
+    tag = "documents",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Upload ID"),
+        ("Tus-Resumable" = String, Header, description = "Protocol version, 1.0.0")
+    ),
+    responses(
+        (status = 204, description = "Upload removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Upload not found"),
+        (status = 412, description = "Unsupported tus version"),
+        (status = 423, description = "A chunk of the upload is being written"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn terminate_upload(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    require_tus_version(&headers)?;
+    let upload = find_upload(&state, id, &auth_user).await?;
+    let Some(_lock) = upload_service::lock_upload(upload.id) else {
+        return Err(StatusCode::LOCKED);
+    };
+
+    UploadService::new(state.clone()).discard(upload.id).await.map_err(|e| {
+        error!("Failed to remove upload {}: {}", upload.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    build(tus_response(StatusCode::NO_CONTENT))
+}
@@ -2,7 +2,7 @@ use axum::{
     extract::{Path, State},
     http::StatusCode,
     response::Json,
-    routing::get,
+    routing::{delete, get, post, put},
     Router,
 };
 use serde::{Deserialize, Serialize};
@@ -58,6 +58,28 @@ fn can_access_user_data(auth_user: &AuthUser, target_user_id: Uuid) -> Result<()
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(list_users).post(create_user))
+        .route("/me/tokens", get(crate::routes::api_tokens::list_api_tokens).post(crate::routes::api_tokens::create_api_token))
+        .route("/me/tokens/{id}", delete(crate::routes::api_tokens::delete_api_token))
+        .route("/{id}/2fa", delete(crate::routes::two_factor::reset_user_two_factor))
+        .route("/me/passkeys", get(crate::routes::passkeys::list_passkeys))
+        .route("/me/passkeys/register/start", post(crate::routes::passkeys::start_passkey_registration))
+        .route("/me/passkeys/register/finish", post(crate::routes::passkeys::finish_passkey_registration))
+        .route("/me/passkeys/{id}", put(crate::routes::passkeys::rename_passkey).delete(crate::routes::passkeys::delete_passkey))
+        .route("/me/sessions", get(crate::routes::sessions::list_sessions).delete(crate::routes::sessions::revoke_other_sessions))
+        .route("/me/sessions/{id}", delete(crate::routes::sessions::revoke_session))
+        .route("/{id}/sessions", delete(crate::routes::sessions::revoke_user_sessions))
+        .route("/{id}/impersonate", post(crate::routes::sessions::impersonate_user))
+        .route("/me/usage", get(crate::routes::storage_quotas::get_my_usage))
+        .route("/me/locale", get(crate::routes::i18n::get_my_locale).put(crate::routes::i18n::update_my_locale))
+        .route(
+            "/me/calendar-feed",
+            get(crate::routes::calendar::get_calendar_feed)
+                .post(crate::routes::calendar::create_calendar_feed)
+                .put(crate::routes::calendar::update_calendar_feed)
+                .delete(crate::routes::calendar::delete_calendar_feed),
+        )
+        .route("/{id}/usage", get(crate::routes::storage_quotas::get_user_usage))
+        .route("/{id}/quota", put(crate::routes::storage_quotas::set_user_quota).delete(crate::routes::storage_quotas::delete_user_quota))
         .route("/{id}", get(get_user).put(update_user).delete(delete_user))
         .route("/{id}/watch-directory", get(get_user_watch_directory).post(create_user_watch_directory).delete(delete_user_watch_directory))
 }
@@ -357,19 +357,19 @@ async fn start_webdav_sync(
                 info!("WebDAV sync completed successfully for user {}: {} files processed", user_id, files_processed);
                 
                 // Send success notification
+                let key = if files_processed > 0 {
+                    "notification.manual_webdav_sync_completed"
+                } else {
+                    "notification.manual_webdav_sync_no_files"
+                };
+                let locale = crate::services::i18n::locale_of(&state_clone.db, user_id).await;
                 let notification = crate::models::CreateNotification {
-                    notification_type: "success".to_string(),
-                    title: "Manual WebDAV Sync Completed".to_string(),
-                    message: if files_processed > 0 {
-                        format!("Successfully processed {} files from manual WebDAV sync", files_processed)
-                    } else {
-                        "Manual WebDAV sync completed - no new files found".to_string()
-                    },
                     action_url: Some("/documents".to_string()),
                     metadata: Some(serde_json::json!({
                         "sync_type": "webdav_manual",
                         "files_processed": files_processed
                     })),
+                    ..crate::services::i18n::notification(&locale, "success", key, &[("files", files_processed.to_string())])
                 };
                 
                 if let Err(e) = state_clone.db.create_notification(user_id, &notification).await {
@@ -380,15 +380,14 @@ async fn start_webdav_sync(
                 error!("WebDAV sync failed for user {}: {}", user_id, e);
                 
                 // Send error notification
+                let locale = crate::services::i18n::locale_of(&state_clone.db, user_id).await;
                 let notification = crate::models::CreateNotification {
-                    notification_type: "error".to_string(),
-                    title: "Manual WebDAV Sync Failed".to_string(),
-                    message: format!("Manual WebDAV sync encountered an error: {}", e),
                     action_url: Some("/settings".to_string()),
                     metadata: Some(serde_json::json!({
                         "sync_type": "webdav_manual",
                         "error": e.to_string()
                     })),
+                    ..crate::services::i18n::notification(&locale, "error", "notification.manual_webdav_sync_failed", &[("error", e.to_string())])
                 };
                 
                 if let Err(e) = state_clone.db.create_notification(user_id, &notification).await {
@@ -446,15 +445,14 @@ async fn cancel_webdav_sync(
             info!("WebDAV sync cancelled for user {}", auth_user.user.id);
             
             // Send cancellation notification
+            let locale = crate::services::i18n::locale_of(&state.db, auth_user.user.id).await;
             let notification = crate::models::CreateNotification {
-                notification_type: "info".to_string(),
-                title: "WebDAV Sync Cancelled".to_string(),
-                message: "WebDAV sync was cancelled by user request".to_string(),
                 action_url: Some("/settings".to_string()),
                 metadata: Some(serde_json::json!({
                     "sync_type": "webdav_manual",
                     "cancelled": true
                 })),
+                ..crate::services::i18n::notification(&locale, "info", "notification.webdav_sync_cancelled", &[])
             };
             
             if let Err(e) = state.db.create_notification(auth_user.user.id, &notification).await {
@@ -454,6 +454,11 @@ async fn process_single_file(
                    file_request_id, file_info.name, doc.id, file_change_reason);
             (doc, true, "synced") // New document - queue for OCR
         }
+        IngestionResult::NewVersion(doc) => {
+            debug!("[{}] 🆕 Stored '{}' as a new version of document {} (reason: {})", 
+                   file_request_id, file_info.name, doc.id, file_change_reason);
+            (doc, true, "synced") // New contents - queue for OCR
+        }
         IngestionResult::ExistingDocument(doc) => {
             debug!("[{}] 🔗 Found existing document for '{}': {} (reason: {})", 
                    file_request_id, file_info.name, doc.id, file_change_reason);
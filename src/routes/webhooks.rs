@@ -0,0 +1,362 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        CreateWebhookEndpointRequest, UpdateWebhookEndpointRequest, WebhookDelivery, WebhookDeliveryQuery,
+        WebhookEndpoint, WebhookEndpointWithSecret,
+    },
+    services::event_service::{self, MIN_SECRET_LENGTH},
+    AppState,
+};
+
+const DEFAULT_DELIVERY_LIMIT: i64 = 50;
+const MAX_DELIVERY_LIMIT: i64 = 200;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_webhook_endpoints).post(create_webhook_endpoint))
+        .route(
+            "/{id}",
+            get(get_webhook_endpoint)
+                .put(update_webhook_endpoint)
+                .delete(delete_webhook_endpoint),
+        )
+        .route("/{id}/secret", post(rotate_webhook_secret))
+        .route("/{id}/deliveries", get(list_webhook_deliveries))
+        .route("/{id}/deliveries/{delivery_id}/retry", post(retry_webhook_delivery))
+}
+
+fn validate_url(url: &str) -> Result<(), StatusCode> {
+    let target = url::Url::parse(url).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if target.scheme() != "http" && target.scheme() != "https" {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Webhook endpoints of the current user", body = Vec<WebhookEndpoint>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_webhook_endpoints(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<WebhookEndpoint>>, StatusCode> {
+    let endpoints = state
+        .db
+        .get_webhook_endpoints(auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to list webhook endpoints: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(endpoints))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateWebhookEndpointRequest,
+    responses(
+        (status = 201, description = "Webhook endpoint created, with its signing secret", body = WebhookEndpointWithSecret),
+        (status = 400, description = "Invalid URL, or secret shorter than 16 characters"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_webhook_endpoint(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateWebhookEndpointRequest>,
+) -> Result<(StatusCode, Json<WebhookEndpointWithSecret>), StatusCode> {
+    validate_url(&request.url)?;
+
+    let secret = match request.secret.as_deref().map(str::trim) {
+        Some(secret) if secret.chars().count() < MIN_SECRET_LENGTH => return Err(StatusCode::BAD_REQUEST),
+        Some(secret) => secret.to_string(),
+        None => event_service::generate_secret(),
+    };
+
+    let endpoint = state
+        .db
+        .create_webhook_endpoint(auth_user.user.id, &request, &secret)
+        .await
+        .map_err(|e| {
+            error!("Failed to create webhook endpoint: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok((StatusCode::CREATED, Json(endpoint.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Webhook endpoint ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook endpoint", body = WebhookEndpoint),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook endpoint not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_webhook_endpoint(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WebhookEndpoint>, StatusCode> {
+    let endpoint = state
+        .db
+        .get_webhook_endpoint(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get webhook endpoint {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(endpoint))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Webhook endpoint ID")
+    ),
+    request_body = UpdateWebhookEndpointRequest,
+    responses(
+        (status = 200, description = "Webhook endpoint updated", body = WebhookEndpoint),
+        (status = 400, description = "Invalid URL"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook endpoint not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_webhook_endpoint(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateWebhookEndpointRequest>,
+) -> Result<Json<WebhookEndpoint>, StatusCode> {
+    if let Some(url) = &request.url {
+        validate_url(url)?;
+    }
+
+    let endpoint = state
+        .db
+        .update_webhook_endpoint(id, auth_user.user.id, &request)
+        .await
+        .map_err(|e| {
+            error!("Failed to update webhook endpoint {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(endpoint))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Webhook endpoint ID")
+    ),
+    responses(
+        (status = 204, description = "Webhook endpoint deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook endpoint not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_webhook_endpoint(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state
+        .db
+        .delete_webhook_endpoint(id, auth_user.user.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete webhook endpoint {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Replace an endpoint's signing secret with a new random one
+#[utoipa::path(
+    post,
+    path = "/api/webhooks/{id}/secret",
+    tag = "webhooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Webhook endpoint ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook endpoint with its new secret", body = WebhookEndpointWithSecret),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook endpoint not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn rotate_webhook_secret(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WebhookEndpointWithSecret>, StatusCode> {
+    let endpoint = state
+        .db
+        .set_webhook_endpoint_secret(id, auth_user.user.id, &event_service::generate_secret())
+        .await
+        .map_err(|e| {
+            error!("Failed to rotate secret of webhook endpoint {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(endpoint.into()))
+}
+
+/// Delivery log of an endpoint, newest first
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Webhook endpoint ID"),
+        WebhookDeliveryQuery
+    ),
+    responses(
+        (status = 200, description = "Deliveries with their attempts and last response", body = Vec<WebhookDelivery>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook endpoint not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_webhook_deliveries(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(query): Query<WebhookDeliveryQuery>,
+) -> Result<Json<Vec<WebhookDelivery>>, StatusCode> {
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to list deliveries of webhook endpoint {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    state
+        .db
+        .get_webhook_endpoint(id, auth_user.user.id)
+        .await
+        .map_err(db_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let deliveries = state
+        .db
+        .get_webhook_deliveries(
+            id,
+            query.status,
+            query.limit.unwrap_or(DEFAULT_DELIVERY_LIMIT).clamp(1, MAX_DELIVERY_LIMIT),
+            query.offset.unwrap_or(0).max(0),
+        )
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(deliveries))
+}
+
+/// Send a delivery again with a fresh set of attempts, within the next 30 seconds
+#[utoipa::path(
+    post,
+    path = "/api/webhooks/{id}/deliveries/{delivery_id}/retry",
+    tag = "webhooks",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Webhook endpoint ID"),
+        ("delivery_id" = Uuid, Path, description = "Webhook delivery ID")
+    ),
+    responses(
+        (status = 202, description = "Delivery queued", body = WebhookDelivery),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook endpoint or delivery not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn retry_webhook_delivery(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, Json<WebhookDelivery>), StatusCode> {
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to retry webhook delivery {}: {}", delivery_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    state
+        .db
+        .get_webhook_endpoint(id, auth_user.user.id)
+        .await
+        .map_err(db_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let delivery = state
+        .db
+        .retry_webhook_delivery(delivery_id, id)
+        .await
+        .map_err(db_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok((StatusCode::ACCEPTED, Json(delivery)))
+}
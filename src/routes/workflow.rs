@@ -0,0 +1,287 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{debug, error};
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        DocumentResponse, SkippedTransition, WorkflowCounts, WorkflowDocument, WorkflowDocumentsQuery, WorkflowSettings,
+        WorkflowState, WorkflowTransitionRequest, WorkflowTransitionResponse,
+    },
+    services::audit_service::{self, AuditEvent, ClientInfo},
+    AppState,
+};
+
+/// Most documents listed per page
+const MAX_PAGE_SIZE: i64 = 200;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/counts", get(get_counts))
+        .route("/documents", get(list_documents))
+        .route("/transition", post(transition_documents))
+        .route("/settings", get(get_settings).put(update_settings))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Number of documents in each workflow state
+#[utoipa::path(
+    get,
+    path = "/api/workflow/counts",
+    tag = "workflow",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Documents per state", body = WorkflowCounts),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_counts(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<WorkflowCounts>, StatusCode> {
+    let counts = state
+        .db
+        .get_workflow_counts(auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| internal_error("Failed to count documents per workflow state", e))?;
+
+    Ok(Json(counts))
+}
+
+/// Documents in a workflow state, oldest first
+///
+/// Without a state this is the inbox: new documents waiting to be reviewed.
+#[utoipa::path(
+    get,
+    path = "/api/workflow/documents",
+    tag = "workflow",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(WorkflowDocumentsQuery),
+    responses(
+        (status = 200, description = "Documents in the state", body = Vec<WorkflowDocument>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Query(query): Query<WorkflowDocumentsQuery>,
+) -> Result<Json<Vec<WorkflowDocument>>, StatusCode> {
+    let limit = query.limit.unwrap_or(50).clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let documents = state
+        .db
+        .get_workflow_documents(
+            auth_user.user.id,
+            auth_user.user.role,
+            query.state.unwrap_or(WorkflowState::Inbox),
+            limit,
+            offset,
+        )
+        .await
+        .map_err(|e| internal_error("Failed to list documents by workflow state", e))?;
+
+    Ok(Json(
+        documents
+            .into_iter()
+            .map(|(document, workflow_state, workflow_state_changed_at)| WorkflowDocument {
+                document: DocumentResponse::from(document),
+                workflow_state,
+                workflow_state_changed_at,
+            })
+            .collect(),
+    ))
+}
+
+/// Move documents to another workflow state
+///
+/// Documents go from the inbox to reviewed or rejected, and from reviewed to filed,
+/// rejected or back to the inbox; filed and rejected documents can be sent back to the
+/// inbox. Documents that cannot make the move, are already in the state, or may not be
+/// edited by the user are skipped and listed with the reason.
+#[utoipa::path(
+    post,
+    path = "/api/workflow/transition",
+    tag = "workflow",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = WorkflowTransitionRequest,
+    responses(
+        (status = 200, description = "Documents moved and skipped", body = WorkflowTransitionResponse),
+        (status = 400, description = "No documents, or too many"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn transition_documents(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<WorkflowTransitionRequest>,
+) -> Result<Json<WorkflowTransitionResponse>, StatusCode> {
+    if let Err(reason) = request.validate() {
+        debug!("Rejected workflow transition: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let to = request.to_state;
+    let current: HashMap<_, _> = state
+        .db
+        .get_workflow_states(&request.document_ids, auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| internal_error("Failed to get workflow states", e))?
+        .into_iter()
+        .collect();
+
+    let mut skipped = Vec::new();
+    let mut movable = Vec::new();
+    let mut seen = HashSet::new();
+    for document_id in &request.document_ids {
+        match current.get(document_id) {
+            None => skipped.push(SkippedTransition {
+                document_id: *document_id,
+                state: None,
+                reason: "Document not found".to_string(),
+            }),
+            Some(&from) if from == to => skipped.push(SkippedTransition {
+                document_id: *document_id,
+                state: Some(from),
+                reason: format!("Already {}", to.as_str()),
+            }),
+            Some(&from) if !from.can_transition_to(to) => skipped.push(SkippedTransition {
+                document_id: *document_id,
+                state: Some(from),
+                reason: format!("Cannot move from {} to {}", from.as_str(), to.as_str()),
+            }),
+            Some(_) => {
+                if seen.insert(*document_id) {
+                    movable.push(*document_id);
+                }
+            }
+        }
+    }
+
+    let updated = if movable.is_empty() {
+        Vec::new()
+    } else {
+        state
+            .db
+            .transition_documents(&movable, to, auth_user.user.id)
+            .await
+            .map_err(|e| internal_error("Failed to change workflow states", e))?
+    };
+
+    // Another request may have moved a document between the check and the update
+    let moved: HashSet<_> = updated.iter().collect();
+    for document_id in movable.iter().filter(|id| !moved.contains(id)) {
+        skipped.push(SkippedTransition {
+            document_id: *document_id,
+            state: None,
+            reason: "State changed while moving".to_string(),
+        });
+    }
+
+    for document_id in &updated {
+        audit_service::record(
+            &state.db,
+            Some(&auth_user.user),
+            &client,
+            AuditEvent::new(audit_service::DOCUMENT_WORKFLOW_TRANSITION, "document", Some(*document_id))
+                .before(serde_json::json!({ "workflow_state": current.get(document_id).map(|s| s.as_str()) }))
+                .after(serde_json::json!({ "workflow_state": to.as_str() })),
+        )
+        .await;
+    }
+
+    let counts = state
+        .db
+        .get_workflow_counts(auth_user.user.id, auth_user.user.role)
+        .await
+        .map_err(|e| internal_error("Failed to count documents per workflow state", e))?;
+
+    Ok(Json(WorkflowTransitionResponse { to_state: to, updated, skipped, counts }))
+}
+
+/// Whether documents the current user has not reviewed are left out of search
+#[utoipa::path(
+    get,
+    path = "/api/workflow/settings",
+    tag = "workflow",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Workflow settings", body = WorkflowSettings),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_settings(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<WorkflowSettings>, StatusCode> {
+    let require_review_before_search = state
+        .db
+        .get_require_review_before_search(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to get workflow settings", e))?;
+
+    Ok(Json(WorkflowSettings { require_review_before_search }))
+}
+
+/// Choose whether documents in the inbox or rejected are left out of search
+#[utoipa::path(
+    put,
+    path = "/api/workflow/settings",
+    tag = "workflow",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = WorkflowSettings,
+    responses(
+        (status = 200, description = "Updated workflow settings", body = WorkflowSettings),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    Json(request): Json<WorkflowSettings>,
+) -> Result<Json<WorkflowSettings>, StatusCode> {
+    state
+        .db
+        .set_require_review_before_search(auth_user.user.id, request.require_review_before_search)
+        .await
+        .map_err(|e| internal_error("Failed to update workflow settings", e))?;
+
+    audit_service::record(
+        &state.db,
+        Some(&auth_user.user),
+        &client,
+        AuditEvent::new(audit_service::SETTINGS_UPDATE, "settings", None)
+            .after(serde_json::json!({ "require_review_before_search": request.require_review_before_search })),
+    )
+    .await;
+
+    Ok(Json(request))
+}
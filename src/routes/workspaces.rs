@@ -0,0 +1,446 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{delete, get},
+    Router,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        CreateWorkspaceRequest, SetWorkspaceMemberRequest, UpdateWorkspaceRequest, UserRole, Workspace,
+        WorkspaceMember, WorkspaceMembership, WorkspaceRole,
+    },
+    routes::queue::require_admin,
+    services::workspace_service::{self, MAX_NAME_LENGTH},
+    AppState,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_workspaces).post(create_workspace))
+        .route("/mine", get(list_my_workspaces))
+        .route("/{id}", get(get_workspace).put(update_workspace).delete(delete_workspace))
+        .route("/{id}/members", get(list_workspace_members).post(set_workspace_member))
+        .route("/{id}/members/{user_id}", delete(remove_workspace_member))
+}
+
+fn internal_error(context: &str, e: anyhow::Error) -> StatusCode {
+    error!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Workspace routes only exist in multi-tenant mode
+fn require_multi_tenant() -> Result<(), StatusCode> {
+    if workspace_service::enabled() {
+        Ok(())
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+fn is_valid_workspace_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty() && name.len() <= MAX_NAME_LENGTH
+}
+
+/// A workspace the user holds at least `role` in. Workspaces they are not a member of
+/// are reported as missing; a lower role is forbidden.
+async fn workspace_with_role(
+    state: &AppState,
+    auth_user: &AuthUser,
+    id: Uuid,
+    role: WorkspaceRole,
+) -> Result<Workspace, StatusCode> {
+    let workspace = state
+        .db
+        .get_workspace(id)
+        .await
+        .map_err(|e| internal_error("Failed to get workspace", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if auth_user.user.role == UserRole::Admin {
+        return Ok(workspace);
+    }
+    let member_role = state
+        .db
+        .get_workspace_role(id, auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to get workspace role", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if member_role < role {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(workspace)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/workspaces",
+    tag = "workspaces",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Every workspace", body = Vec<Workspace>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Multi-tenant mode is off"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_workspaces(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<Workspace>>, StatusCode> {
+    require_multi_tenant()?;
+    require_admin(&auth_user)?;
+
+    let workspaces = state
+        .db
+        .get_workspaces()
+        .await
+        .map_err(|e| internal_error("Failed to list workspaces", e))?;
+
+    Ok(Json(workspaces))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/workspaces/mine",
+    tag = "workspaces",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Workspaces the current user is a member of, with their role", body = Vec<WorkspaceMembership>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Multi-tenant mode is off"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_my_workspaces(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<WorkspaceMembership>>, StatusCode> {
+    require_multi_tenant()?;
+
+    let workspaces = state
+        .db
+        .get_workspaces_for_user(auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to list workspaces of user", e))?;
+
+    Ok(Json(workspaces))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/workspaces",
+    tag = "workspaces",
+    security(
+        ("bearer_auth" = [])
+    ),
+    request_body = CreateWorkspaceRequest,
+    responses(
+        (status = 201, description = "Workspace created with the current user as its admin", body = Workspace),
+        (status = 400, description = "Empty or too long name, or invalid slug"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Multi-tenant mode is off"),
+        (status = 409, description = "A workspace with this slug exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_workspace(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateWorkspaceRequest>,
+) -> Result<(StatusCode, Json<Workspace>), StatusCode> {
+    require_multi_tenant()?;
+    require_admin(&auth_user)?;
+    if !is_valid_workspace_name(&request.name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let slug = workspace_service::slugify(request.slug.as_deref().unwrap_or(&request.name))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let taken = state
+        .db
+        .workspace_slug_taken(&slug)
+        .await
+        .map_err(|e| internal_error("Failed to check workspace slug", e))?;
+    if taken {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let workspace = state
+        .db
+        .create_workspace(&request.name, &slug, &request.settings.unwrap_or_default(), auth_user.user.id)
+        .await
+        .map_err(|e| internal_error("Failed to create workspace", e))?;
+
+    info!("User {} created workspace '{}' ({})", auth_user.user.id, workspace.slug, workspace.id);
+    Ok((StatusCode::CREATED, Json(workspace)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/workspaces/{id}",
+    tag = "workspaces",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Workspace ID")
+    ),
+    responses(
+        (status = 200, description = "Workspace", body = Workspace),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Workspace not found or the current user is not a member"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_workspace(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Workspace>, StatusCode> {
+    require_multi_tenant()?;
+    workspace_with_role(&state, &auth_user, id, WorkspaceRole::Viewer).await.map(Json)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/workspaces/{id}",
+    tag = "workspaces",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Workspace ID")
+    ),
+    request_body = UpdateWorkspaceRequest,
+    responses(
+        (status = 200, description = "Workspace updated", body = Workspace),
+        (status = 400, description = "Empty or too long name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Workspace admin role required"),
+        (status = 404, description = "Workspace not found or the current user is not a member"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_workspace(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateWorkspaceRequest>,
+) -> Result<Json<Workspace>, StatusCode> {
+    require_multi_tenant()?;
+    workspace_with_role(&state, &auth_user, id, WorkspaceRole::Admin).await?;
+    if request.name.as_deref().is_some_and(|name| !is_valid_workspace_name(name)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let workspace = state
+        .db
+        .update_workspace(id, request.name.as_deref(), request.settings.as_ref())
+        .await
+        .map_err(|e| internal_error("Failed to update workspace", e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(workspace))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/workspaces/{id}",
+    tag = "workspaces",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Workspace ID")
+    ),
+    responses(
+        (status = 204, description = "Workspace deleted with its documents, labels and sources"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin access required"),
+        (status = 404, description = "Workspace not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_workspace(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_multi_tenant()?;
+    require_admin(&auth_user)?;
+
+    let deleted = state
+        .db
+        .delete_workspace(id)
+        .await
+        .map_err(|e| internal_error("Failed to delete workspace", e))?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("User {} deleted workspace {}", auth_user.user.id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/workspaces/{id}/members",
+    tag = "workspaces",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Workspace ID")
+    ),
+    responses(
+        (status = 200, description = "Members of the workspace", body = Vec<WorkspaceMember>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Workspace not found or the current user is not a member"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_workspace_members(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<WorkspaceMember>>, StatusCode> {
+    require_multi_tenant()?;
+    workspace_with_role(&state, &auth_user, id, WorkspaceRole::Viewer).await?;
+
+    let members = state
+        .db
+        .get_workspace_members(id)
+        .await
+        .map_err(|e| internal_error("Failed to list workspace members", e))?;
+
+    Ok(Json(members))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/workspaces/{id}/members",
+    tag = "workspaces",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Workspace ID")
+    ),
+    request_body = SetWorkspaceMemberRequest,
+    responses(
+        (status = 200, description = "Member added or role changed; the members of the workspace", body = Vec<WorkspaceMember>),
+        (status = 400, description = "Unknown user"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Workspace admin role required"),
+        (status = 404, description = "Workspace not found or the current user is not a member"),
+        (status = 409, description = "The change would leave the workspace without an admin"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn set_workspace_member(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SetWorkspaceMemberRequest>,
+) -> Result<Json<Vec<WorkspaceMember>>, StatusCode> {
+    require_multi_tenant()?;
+    workspace_with_role(&state, &auth_user, id, WorkspaceRole::Admin).await?;
+
+    state
+        .db
+        .get_user_by_id(request.user_id)
+        .await
+        .map_err(|e| internal_error("Failed to get user", e))?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let role = request.role.unwrap_or(WorkspaceRole::Editor);
+    if role < WorkspaceRole::Admin {
+        ensure_other_admin(&state, id, request.user_id).await?;
+    }
+
+    state
+        .db
+        .set_workspace_member(id, request.user_id, role)
+        .await
+        .map_err(|e| internal_error("Failed to set workspace member", e))?;
+
+    info!("User {} made {} a {} of workspace {}", auth_user.user.id, request.user_id, role, id);
+    list_workspace_members(State(state), auth_user, Path(id)).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/workspaces/{id}/members/{user_id}",
+    tag = "workspaces",
+    security(
+        ("bearer_auth" = [])
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Workspace ID"),
+        ("user_id" = Uuid, Path, description = "Member to remove")
+    ),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Workspace admin role required"),
+        (status = 404, description = "Workspace or member not found"),
+        (status = 409, description = "The member is the workspace's last admin"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn remove_workspace_member(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    require_multi_tenant()?;
+    workspace_with_role(&state, &auth_user, id, WorkspaceRole::Admin).await?;
+    ensure_other_admin(&state, id, user_id).await?;
+
+    let removed = state
+        .db
+        .remove_workspace_member(id, user_id)
+        .await
+        .map_err(|e| internal_error("Failed to remove workspace member", e))?;
+    if !removed {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("User {} removed {} from workspace {}", auth_user.user.id, user_id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Refuses to demote or remove the workspace's only admin
+async fn ensure_other_admin(state: &AppState, workspace_id: Uuid, user_id: Uuid) -> Result<(), StatusCode> {
+    let role = state
+        .db
+        .get_workspace_role(workspace_id, user_id)
+        .await
+        .map_err(|e| internal_error("Failed to get workspace role", e))?;
+    if role != Some(WorkspaceRole::Admin) {
+        return Ok(());
+    }
+
+    let admins = state
+        .db
+        .count_workspace_admins(workspace_id)
+        .await
+        .map_err(|e| internal_error("Failed to count workspace admins", e))?;
+    if admins <= 1 {
+        return Err(StatusCode::CONFLICT);
+    }
+    Ok(())
+}
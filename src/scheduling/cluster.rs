@@ -0,0 +1,262 @@
+//! Several processes sharing one database: which work this process takes on
+//! (`NODE_ROLES`), its row in `worker_nodes`, and the advisory locks keeping
+//! background tasks that must not run twice on a single node
+
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use sqlx::{Connection, PgConnection};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::worker_nodes::NodeStatus;
+use crate::db::Database;
+use crate::models::{parse_node_roles, NodeRole};
+use crate::ocr::queue::OcrQueueService;
+
+/// How often a node reports; a node silent for a minute is taken to be gone
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often a node not holding a task's lock tries to take it over
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Heartbeats between removals of nodes gone for days
+const PRUNE_EVERY_HEARTBEATS: u32 = 240;
+
+static LOCAL_NODE: OnceLock<NodeConfig> = OnceLock::new();
+
+/// This process, as other nodes see it
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    /// New on every start, so work claimed by an earlier run counts as abandoned
+    pub id: Uuid,
+    pub name: String,
+    pub roles: Vec<NodeRole>,
+}
+
+impl NodeConfig {
+    /// `NODE_NAME`, the hostname when not set, and the `NODE_ROLES`, all when not set
+    pub fn from_env() -> Result<Self, String> {
+        let name = std::env::var("NODE_NAME")
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(hostname);
+        let roles = match std::env::var("NODE_ROLES") {
+            Ok(value) => parse_node_roles(&value).map_err(|e| format!("Invalid NODE_ROLES: {}", e))?,
+            Err(_) => NodeRole::ALL.to_vec(),
+        };
+
+        Ok(Self { id: Uuid::new_v4(), name, roles })
+    }
+
+    pub fn runs(&self, role: NodeRole) -> bool {
+        self.roles.contains(&role)
+    }
+}
+
+/// Set the configuration of this process; the first call wins
+pub fn init(config: NodeConfig) -> &'static NodeConfig {
+    LOCAL_NODE.get_or_init(|| config)
+}
+
+/// This process; one running every role unless [`init`] said otherwise
+pub fn local_node() -> &'static NodeConfig {
+    LOCAL_NODE.get_or_init(|| NodeConfig {
+        id: Uuid::new_v4(),
+        name: hostname(),
+        roles: NodeRole::ALL.to_vec(),
+    })
+}
+
+/// Whether this process takes on the role
+pub fn runs(role: NodeRole) -> bool {
+    local_node().runs(role)
+}
+
+fn hostname() -> String {
+    hostname::get().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|_| "readur".to_string())
+}
+
+/// Advisory lock key of a task, the same in every build and on every node
+fn lock_key(name: &str) -> i64 {
+    let digest = Sha256::digest(format!("readur:exclusive:{}", name).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    i64::from_be_bytes(bytes)
+}
+
+/// Session-level advisory locks held on one connection of their own. Postgres releases
+/// them when the connection drops, so a node that dies hands its tasks over and a node
+/// that loses its connection stops them.
+pub struct ClusterLocks {
+    database_url: String,
+    connection: tokio::sync::Mutex<Option<PgConnection>>,
+    /// Bumped whenever the connection is lost, taking every lock held with it
+    generation: watch::Sender<u64>,
+    held: Mutex<BTreeSet<String>>,
+}
+
+impl ClusterLocks {
+    pub fn new(database_url: &str) -> Arc<Self> {
+        Arc::new(Self {
+            database_url: database_url.to_string(),
+            connection: tokio::sync::Mutex::new(None),
+            generation: watch::Sender::new(0),
+            held: Mutex::new(BTreeSet::new()),
+        })
+    }
+
+    /// Names of the tasks running here under their lock
+    pub fn held(&self) -> Vec<String> {
+        self.held.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// The generation the lock was taken in, or None when another node holds it
+    async fn try_lock(&self, name: &str) -> Result<Option<u64>> {
+        let mut connection = self.connection.lock().await;
+        if connection.is_none() {
+            *connection = Some(PgConnection::connect(&self.database_url).await?);
+        }
+        let conn = connection.as_mut().expect("connection was just opened");
+        let generation = *self.generation.borrow();
+        match sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
+            .bind(lock_key(name))
+            .fetch_one(&mut *conn)
+            .await
+        {
+            Ok(true) => Ok(Some(generation)),
+            Ok(false) => Ok(None),
+            Err(e) => {
+                *connection = None;
+                self.generation.send_modify(|generation| *generation += 1);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn unlock(&self, name: &str) {
+        let mut connection = self.connection.lock().await;
+        if let Some(conn) = connection.as_mut() {
+            if let Err(e) = sqlx::query("SELECT pg_advisory_unlock($1)").bind(lock_key(name)).execute(conn).await {
+                warn!("Failed to release the cluster lock of {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Check the connection is alive; when it is not, the tasks it held locks for stop
+    async fn check_connection(&self) {
+        let mut connection = self.connection.lock().await;
+        let Some(conn) = connection.as_mut() else {
+            return;
+        };
+        if let Err(e) = conn.ping().await {
+            warn!("Lost the connection holding cluster locks: {}", e);
+            *connection = None;
+            self.generation.send_modify(|generation| *generation += 1);
+        }
+    }
+
+    /// Run a task on one node at a time. Until this node holds the task's lock it keeps
+    /// trying; when the lock is lost the task stops and is started again from `task`
+    /// once the lock is back. Returns when the task does.
+    pub async fn run_exclusive<F, Fut>(self: Arc<Self>, name: &'static str, task: F)
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut waiting_logged = false;
+        loop {
+            match self.try_lock(name).await {
+                Ok(Some(generation)) => {
+                    info!("Running {} on this node", name);
+                    waiting_logged = false;
+                    self.held.lock().unwrap().insert(name.to_string());
+                    let mut generations = self.generation.subscribe();
+                    tokio::select! {
+                        _ = task() => {
+                            self.held.lock().unwrap().remove(name);
+                            self.unlock(name).await;
+                            return;
+                        }
+                        _ = async {
+                            let _ = generations.wait_for(|current| *current != generation).await;
+                        } => {
+                            self.held.lock().unwrap().remove(name);
+                            warn!("Stopped {} after losing its cluster lock", name);
+                        }
+                    }
+                }
+                Ok(None) => {
+                    if !waiting_logged {
+                        info!("{} runs on another node", name);
+                        waiting_logged = true;
+                    }
+                }
+                Err(e) => warn!("Failed to take the cluster lock of {}: {}", name, e),
+            }
+            tokio::time::sleep(LOCK_RETRY_INTERVAL).await;
+        }
+    }
+}
+
+/// Record this node in `worker_nodes`. An earlier run under the same name on this host
+/// is marked stopped, so what it left running is taken over straight away.
+pub async fn register(db: &Database) -> Result<()> {
+    let node = local_node();
+    let roles: Vec<String> = node.roles.iter().map(|role| role.to_string()).collect();
+    db.register_worker_node(node.id, &node.name, &hostname(), &roles, env!("CARGO_PKG_VERSION")).await?;
+    info!("Registered node {} ({}) with roles {}", node.name, node.id, roles.join(", "));
+
+    Ok(())
+}
+
+/// Keep this node's row current, with its OCR load and the exclusive tasks it runs
+pub async fn run_heartbeat(db: Database, locks: Arc<ClusterLocks>, ocr_queue: Option<Arc<OcrQueueService>>) {
+    let node_id = local_node().id;
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut beats: u32 = 0;
+    loop {
+        interval.tick().await;
+        if super::shutdown::requested() {
+            return;
+        }
+        locks.check_connection().await;
+
+        let exclusive_tasks = locks.held();
+        let status = NodeStatus {
+            ocr_worker_id: ocr_queue.as_ref().map(|queue| queue.worker_id()),
+            active_ocr_jobs: ocr_queue.as_ref().map_or(0, |queue| queue.active_jobs() as i32),
+            max_ocr_jobs: ocr_queue.as_ref().map_or(0, |queue| queue.max_concurrent_jobs() as i32),
+            exclusive_tasks: &exclusive_tasks,
+        };
+        if let Err(e) = db.heartbeat_worker_node(node_id, &status).await {
+            error!("Failed to report node heartbeat: {}", e);
+        }
+
+        if beats % PRUNE_EVERY_HEARTBEATS == 0 {
+            match db.prune_worker_nodes().await {
+                Ok(pruned) if pruned > 0 => info!("Removed {} nodes gone for days", pruned),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to remove old nodes: {}", e),
+            }
+        }
+        beats = beats.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_key_is_stable_and_distinct() {
+        assert_eq!(lock_key("retention"), lock_key("retention"));
+        assert_ne!(lock_key("retention"), lock_key("digests"));
+    }
+}
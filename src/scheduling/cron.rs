@@ -0,0 +1,191 @@
+//! Five-field cron expressions (minute hour day-of-month month day-of-week) for
+//! scheduled source syncs, evaluated in UTC
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+
+const MONTH_NAMES: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const DAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// The minutes a schedule fires at, one bit per allowed value of each field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Both day fields are restricted, so a day matching either fires, as in Vixie cron
+    either_day: bool,
+}
+
+impl CronSchedule {
+    /// Parse `*`, values, ranges, steps and lists such as `*/15 8-18 * * mon-fri`,
+    /// or one of `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly`
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = expression.trim();
+        let expanded = match expression.to_ascii_lowercase().as_str() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            _ => expression,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let &[minute, hour, day_of_month, month, day_of_week] = fields.as_slice() else {
+            return Err(format!(
+                "'{}' does not have the five fields minute, hour, day of month, month and day of week",
+                expression
+            ));
+        };
+
+        // Sunday is 0 or 7
+        let mut days_of_week = parse_field(day_of_week, 0, 7, DAY_NAMES)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days_of_month: parse_field(day_of_month, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, MONTH_NAMES)?,
+            days_of_week,
+            either_day: !day_of_month.starts_with('*') && !day_of_week.starts_with('*'),
+        })
+    }
+
+    /// The first minute the schedule fires at after `after`, or None when it never
+    /// does, like `0 0 31 2 *`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.naive_utc().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        // A schedule for February 29 can be four years away
+        let limit = time + Duration::days(4 * 366);
+        while time <= limit {
+            let date = time.date();
+            if !has(self.months, date.month()) {
+                let next_month = if date.month() == 12 {
+                    NaiveDate::from_ymd_opt(date.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1)
+                };
+                time = next_month?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(date) {
+                time = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !has(self.hours, time.hour()) {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + Duration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time.and_utc());
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day_of_month = has(self.days_of_month, date.day());
+        let day_of_week = has(self.days_of_week, date.weekday().num_days_from_sunday());
+        if self.either_day {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// One field as a bit set; `names` stand for the values from `min` on
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("'{}' has an invalid step", part))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max, names)?, parse_value(end, min, max, names)?)
+        } else {
+            let start = parse_value(range, min, max, names)?;
+            // `5/15` runs from 5 to the end of the field
+            (start, if part.contains('/') { max } else { start })
+        };
+        if start > end {
+            return Err(format!("'{}' is a range that ends before it starts", part));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    if let Some(index) = names.iter().position(|name| name.eq_ignore_ascii_case(value)) {
+        return Ok(min + index as u32);
+    }
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|value| (min..=max).contains(value))
+        .ok_or_else(|| format!("'{}' is not a value from {} to {}", value, min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        CronSchedule::parse(expression).unwrap().next_after(after)
+    }
+
+    #[test]
+    fn test_next_after() {
+        assert_eq!(next("0 2 * * *", at(2024, 5, 10, 1, 30)), Some(at(2024, 5, 10, 2, 0)));
+        assert_eq!(next("0 2 * * *", at(2024, 5, 10, 2, 0)), Some(at(2024, 5, 11, 2, 0)));
+        assert_eq!(next("*/15 * * * *", at(2024, 5, 10, 1, 31)), Some(at(2024, 5, 10, 1, 45)));
+        assert_eq!(next("30 8-18/4 * * *", at(2024, 5, 10, 13, 0)), Some(at(2024, 5, 10, 16, 30)));
+        // Friday May 10th to Monday May 13th
+        assert_eq!(next("0 9 * * mon-fri", at(2024, 5, 10, 10, 0)), Some(at(2024, 5, 13, 9, 0)));
+        assert_eq!(next("0 0 1 jan *", at(2024, 5, 10, 0, 0)), Some(at(2025, 1, 1, 0, 0)));
+        assert_eq!(next("0 0 29 2 *", at(2024, 3, 1, 0, 0)), Some(at(2028, 2, 29, 0, 0)));
+        assert_eq!(next("0 0 31 2 *", at(2024, 3, 1, 0, 0)), None);
+        assert_eq!(next("@daily", at(2024, 12, 31, 23, 59)), Some(at(2025, 1, 1, 0, 0)));
+    }
+
+    #[test]
+    fn test_days_of_month_or_week() {
+        // The 15th or a Sunday: Sunday May 12th comes first
+        assert_eq!(next("0 0 15 * 0", at(2024, 5, 10, 0, 0)), Some(at(2024, 5, 12, 0, 0)));
+        // Sunday is 7 as well
+        assert_eq!(next("0 0 * * 7", at(2024, 5, 10, 0, 0)), Some(at(2024, 5, 12, 0, 0)));
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(CronSchedule::parse("0 2 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("0 24 * * *").is_err());
+        assert!(CronSchedule::parse("0 0 0 * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("0 18-8 * * *").is_err());
+        assert!(CronSchedule::parse("0 0 * * funday").is_err());
+    }
+}
@@ -1,5 +1,10 @@
+pub mod cluster;
+pub mod cron;
+pub mod shutdown;
 pub mod source_scheduler;
 pub mod source_sync;
+pub mod sync_runs;
+pub mod sync_schedule;
 pub mod user_watch_manager;
 pub mod webdav_scheduler;
-pub mod watcher;
\ No newline at end of file
+pub mod watcher;
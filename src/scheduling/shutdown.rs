@@ -0,0 +1,127 @@
+//! Stopping on SIGTERM or Ctrl-C without losing work: once asked to stop, workers take
+//! no new jobs, running OCR jobs and analysis batches finish or stop at their next
+//! checkpoint, and whatever is still running after the grace period is handed back
+//! to the queue
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// How long running jobs get to finish, unless `SHUTDOWN_GRACE_PERIOD_SECONDS` says
+/// otherwise; a little below the 30 seconds Kubernetes waits before killing a pod
+const DEFAULT_GRACE_PERIOD_SECONDS: u64 = 25;
+
+static SHUTDOWN: Lazy<CancellationToken> = Lazy::new(CancellationToken::new);
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+static IN_FLIGHT_DONE: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// Stop once the process receives SIGTERM or Ctrl-C
+pub fn listen() {
+    tokio::spawn(async {
+        wait_for_signal().await;
+        info!("Shutdown requested, no longer taking new jobs");
+        SHUTDOWN.cancel();
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+        Err(e) => {
+            warn!("Failed to listen for SIGTERM, stopping on Ctrl-C only: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Whether the process is stopping; workers check this before taking a job
+pub fn requested() -> bool {
+    SHUTDOWN.is_cancelled()
+}
+
+/// Resolves once the process is stopping
+pub async fn wait() {
+    SHUTDOWN.cancelled().await
+}
+
+/// `SHUTDOWN_GRACE_PERIOD_SECONDS`
+pub fn grace_period() -> Duration {
+    let seconds = std::env::var("SHUTDOWN_GRACE_PERIOD_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_GRACE_PERIOD_SECONDS);
+    Duration::from_secs(seconds)
+}
+
+/// Held by a running job, so shutdown waits for it
+pub struct InFlight(());
+
+/// Count a job as running until the returned guard is dropped
+pub fn track() -> InFlight {
+    IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+    InFlight(())
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if IN_FLIGHT.fetch_sub(1, Ordering::SeqCst) == 1 {
+            IN_FLIGHT_DONE.notify_waiters();
+        }
+    }
+}
+
+/// Jobs running right now
+pub fn in_flight() -> usize {
+    IN_FLIGHT.load(Ordering::SeqCst)
+}
+
+/// Wait for running jobs until none is left or `deadline`; returns the jobs still
+/// running then
+pub async fn drain(deadline: tokio::time::Instant) -> usize {
+    loop {
+        let done = IN_FLIGHT_DONE.notified();
+        let running = in_flight();
+        if running == 0 {
+            return 0;
+        }
+        if tokio::time::timeout_at(deadline, done).await.is_err() {
+            return in_flight();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_jobs() {
+        let job = track();
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(20);
+        assert!(drain(deadline).await >= 1);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            drop(job);
+        });
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        assert_eq!(drain(deadline).await, 0);
+    }
+}
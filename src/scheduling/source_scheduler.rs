@@ -11,10 +11,13 @@ use sqlx::Row;
 
 use crate::{
     AppState,
-    models::{SourceType, LocalFolderSourceConfig, S3SourceConfig, WebDAVSourceConfig},
+    models::{SourceSchedule, WebDAVSourceConfig, ImapSourceConfig, DropboxSourceConfig, OneDriveSourceConfig, SftpSourceConfig},
     models::source::WebDAVTestConnection,
+    models::QueueKind,
+    services::maintenance_service,
 };
 use super::source_sync::SourceSyncService;
+use super::sync_schedule;
 
 struct SyncHealthAnalysis {
     score_penalty: i32,
@@ -58,6 +61,14 @@ impl SourceScheduler {
         
         loop {
             interval_timer.tick().await;
+            if super::shutdown::requested() {
+                info!("Source sync scheduler stopped for shutdown");
+                return;
+            }
+            // Due syncs start on the first check after the queue is resumed
+            if maintenance_service::holds(QueueKind::Sync) {
+                continue;
+            }
             
             if let Err(e) = self.check_and_sync_sources().await {
                 error!("Error in source sync scheduler: {}", e);
@@ -84,7 +95,15 @@ impl SourceScheduler {
                 error!("Failed to reset stuck syncing sources: {}", e);
             }
         }
-        
+
+        match self.state.db.mark_source_sync_runs_interrupted().await {
+            Ok(interrupted) if interrupted > 0 => {
+                info!("Marked {} sync runs from the previous session as interrupted", interrupted);
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to mark interrupted sync runs: {}", e),
+        }
+
         // Get all enabled sources that might have been interrupted
         let sources = match self.state.db.get_sources_for_sync().await {
             Ok(sources) => {
@@ -122,6 +141,10 @@ impl SourceScheduler {
         let sources = self.state.db.get_sources_for_sync().await?;
         
         for source in sources {
+            if super::shutdown::requested() {
+                break;
+            }
+
             // Skip sources that are already in error status due to configuration issues
             if source.status == crate::models::SourceStatus::Error &&
                source.last_error.as_ref().map(|e| e.contains("Configuration error")).unwrap_or(false) {
@@ -154,7 +177,13 @@ impl SourceScheduler {
             }
             
             // Check if sync is due for this source
-            if self.is_sync_due(&source).await? {
+            let schedule = self.state.db.get_source_schedule(source.id).await?;
+            if self.is_sync_due(&source, schedule.as_ref()) {
+                // Other sync nodes see the same source due; whichever claims it first runs it
+                if !self.state.db.start_sync_atomic(source.id, super::cluster::local_node().id).await? {
+                    continue;
+                }
+                let max_runtime = sync_schedule::max_runtime(schedule.as_ref());
                 info!("Starting background sync for source: {} ({})", source.name, source.source_type);
                 
                 let sync_service = self.sync_service.clone();
@@ -181,8 +210,22 @@ impl SourceScheduler {
                     progress.set_phase(crate::services::webdav::SyncPhase::Initializing);
                     state_clone.sync_progress_tracker.register_sync(source.id, progress.clone());
                     
+                    if let Err(e) = state_clone.db.record_source_sync_started(source_clone.id).await {
+                        error!("Failed to record the start of the sync of source {}: {}", source_clone.name, e);
+                    }
+                    let started = std::time::Instant::now();
+                    let runtime_limit = Self::enforce_max_runtime(&source_clone.name, max_runtime, &cancellation_token);
+                    
                     // Pass cancellation token to sync service
-                    match sync_service.sync_source_with_cancellation(&source_clone, enable_background_ocr, cancellation_token.clone()).await {
+                    let sync_result = sync_service.sync_source_with_cancellation(&source_clone, enable_background_ocr, cancellation_token.clone()).await;
+                    if let Some(runtime_limit) = runtime_limit {
+                        runtime_limit.abort();
+                    }
+                    if let Err(e) = state_clone.db.record_source_sync_duration(source_clone.id, started.elapsed().as_millis() as i64).await {
+                        error!("Failed to record the duration of the sync of source {}: {}", source_clone.name, e);
+                    }
+                    
+                    match sync_result {
                         Ok(files_processed) => {
                             info!("Background sync completed for source {}: {} files processed", 
                                   source_clone.name, files_processed);
@@ -207,19 +250,31 @@ impl SourceScheduler {
                                 error!("Failed to update source sync time: {}", e);
                             }
                             
+                            crate::services::event_service::EventService::new(state_clone.db.clone())
+                                .publish_best_effort(
+                                    crate::models::EventType::SyncCompleted,
+                                    Some(source_clone.user_id),
+                                    Some(source_clone.id),
+                                    serde_json::json!({
+                                        "source_id": source_clone.id,
+                                        "source_type": source_clone.source_type.to_string(),
+                                        "files_processed": files_processed,
+                                        "finished_at": chrono::Utc::now(),
+                                    }),
+                                )
+                                .await;
+                            
                             // Send notification if files were processed
                             if files_processed > 0 {
+                                let locale = crate::services::i18n::locale_of(&state_clone.db, source_clone.user_id).await;
                                 let notification = crate::models::CreateNotification {
-                                    notification_type: "success".to_string(),
-                                    title: "Source Sync Completed".to_string(),
-                                    message: format!("Successfully processed {} files from {}", 
-                                                   files_processed, source_clone.name),
                                     action_url: Some("/documents".to_string()),
                                     metadata: Some(serde_json::json!({
                                         "source_type": source_clone.source_type.to_string(),
                                         "source_id": source_clone.id,
                                         "files_processed": files_processed
                                     })),
+                                    ..crate::services::i18n::notification(&locale, "success", "notification.source_sync_completed", &[("files", files_processed.to_string()), ("source", source_clone.name.clone())])
                                 };
                                 
                                 if let Err(e) = state_clone.db.create_notification(source_clone.user_id, &notification).await {
@@ -230,17 +285,29 @@ impl SourceScheduler {
                         Err(e) => {
                             error!("Background sync failed for source {}: {}", source_clone.name, e);
                             
+                            crate::services::event_service::EventService::new(state_clone.db.clone())
+                                .publish_best_effort(
+                                    crate::models::EventType::SyncFailed,
+                                    Some(source_clone.user_id),
+                                    Some(source_clone.id),
+                                    serde_json::json!({
+                                        "source_id": source_clone.id,
+                                        "source_type": source_clone.source_type.to_string(),
+                                        "error": e.to_string(),
+                                    }),
+                                )
+                                .await;
+                            
                             // Send error notification
+                            let locale = crate::services::i18n::locale_of(&state_clone.db, source_clone.user_id).await;
                             let notification = crate::models::CreateNotification {
-                                notification_type: "error".to_string(),
-                                title: "Source Sync Failed".to_string(),
-                                message: format!("Sync failed for {}: {}", source_clone.name, e),
                                 action_url: Some("/sources".to_string()),
                                 metadata: Some(serde_json::json!({
                                     "source_type": source_clone.source_type.to_string(),
                                     "source_id": source_clone.id,
                                     "error": e.to_string()
                                 })),
+                                ..crate::services::i18n::notification(&locale, "error", "notification.source_sync_failed", &[("source", source_clone.name.clone()), ("error", e.to_string())])
                             };
                             
                             if let Err(e) = state_clone.db.create_notification(source_clone.user_id, &notification).await {
@@ -262,63 +329,57 @@ impl SourceScheduler {
         Ok(())
     }
 
-    async fn is_sync_due(&self, source: &crate::models::Source) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        // Get sync interval from source config
-        let sync_interval_minutes = match source.source_type {
-            SourceType::WebDAV => {
-                let config: WebDAVSourceConfig = serde_json::from_value(source.config.clone())?;
-                if !config.auto_sync { return Ok(false); }
-                config.sync_interval_minutes
-            }
-            SourceType::LocalFolder => {
-                let config: LocalFolderSourceConfig = serde_json::from_value(source.config.clone())?;
-                if !config.auto_sync { return Ok(false); }
-                config.sync_interval_minutes
-            }
-            SourceType::S3 => {
-                let config: S3SourceConfig = serde_json::from_value(source.config.clone())?;
-                if !config.auto_sync { return Ok(false); }
-                config.sync_interval_minutes
-            }
-        };
-        
-        if sync_interval_minutes <= 0 {
-            warn!("Invalid sync interval for source {}: {} minutes", source.name, sync_interval_minutes);
-            return Ok(false);
-        }
-
+    fn is_sync_due(&self, source: &crate::models::Source, schedule: Option<&SourceSchedule>) -> bool {
         // Check if a sync is already running
         if source.status.to_string() == "syncing" {
             info!("Sync already running for source {}", source.name);
-            return Ok(false);
+            return false;
         }
 
-        // Check last sync time
-        if let Some(last_sync) = source.last_sync_at {
-            let elapsed = Utc::now() - last_sync;
-            let elapsed_minutes = elapsed.num_minutes();
-            
-            if elapsed_minutes < sync_interval_minutes as i64 {
-                // Only log this occasionally to avoid spam
-                if elapsed_minutes % 10 == 0 {
-                    crate::debug_log!("SOURCE_SCHEDULER", "Sync not due for source {} (last sync {} minutes ago, interval {} minutes)", 
-                        source.name, elapsed_minutes, sync_interval_minutes);
-                }
-                return Ok(false);
+        let now = Utc::now();
+        let next_sync_at = match sync_schedule::next_sync_at(source, schedule, now) {
+            Ok(Some(next_sync_at)) => next_sync_at,
+            Ok(None) => return false,
+            Err(e) => {
+                warn!("Invalid sync schedule for source {}: {}", source.name, e);
+                return false;
             }
-            
-            info!("Sync is due for source {} (last sync {} minutes ago, interval {} minutes)", 
-                source.name, elapsed_minutes, sync_interval_minutes);
-        } else {
-            info!("No previous sync found for source {}, sync is due", source.name);
+        };
+        if next_sync_at > now {
+            crate::debug_log!("SOURCE_SCHEDULER", "Sync not due for source {} (next sync at {})",
+                source.name, next_sync_at);
+            return false;
+        }
+
+        match source.last_sync_at {
+            Some(last_sync) => info!("Sync is due for source {} (last sync at {})", source.name, last_sync),
+            None => info!("No previous sync found for source {}, sync is due", source.name),
         }
+        true
+    }
 
-        // Sync is due
-        Ok(true)
+    /// Stop the sync behind `cancellation_token` once it has run for the source's
+    /// max runtime. The returned task is aborted when the sync ends first.
+    fn enforce_max_runtime(
+        source_name: &str,
+        max_runtime: Option<Duration>,
+        cancellation_token: &CancellationToken,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let max_runtime = max_runtime?;
+        let source_name = source_name.to_string();
+        let cancellation_token = cancellation_token.clone();
+        Some(tokio::spawn(async move {
+            tokio::time::sleep(max_runtime).await;
+            warn!("Sync of source {} ran longer than {} minutes, stopping it", source_name, max_runtime.as_secs() / 60);
+            cancellation_token.cancel();
+        }))
     }
 
     pub async fn trigger_sync(&self, source_id: uuid::Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Triggering manual sync for source {}", source_id);
+        if maintenance_service::holds(QueueKind::Sync) {
+            return Err("Source syncs are paused".into());
+        }
         
         // Check if sync is already running
         {
@@ -340,13 +401,20 @@ impl SourceScheduler {
         }
         
         // Atomically start the sync - this prevents race conditions
-        if !self.state.db.start_sync_atomic(source_id).await? {
+        if !self.state.db.start_sync_atomic(source_id, super::cluster::local_node().id).await? {
             return Err("Could not start sync - source is already syncing".into());
         }
         
         let sync_service = self.sync_service.clone();
         let state_clone = self.state.clone();
         let running_syncs_clone = self.running_syncs.clone();
+        let max_runtime = match self.state.db.get_source_schedule(source_id).await {
+            Ok(schedule) => sync_schedule::max_runtime(schedule.as_ref()),
+            Err(e) => {
+                warn!("Failed to load the schedule of source {}: {}", source.name, e);
+                None
+            }
+        };
         
         // Create cancellation token for this sync
         let cancellation_token = CancellationToken::new();
@@ -375,17 +443,41 @@ impl SourceScheduler {
                 state_clone.sync_progress_tracker.unregister_sync(source_id);
             };
             
-            // Execute the sync operation with a timeout to prevent hanging
+            if let Err(e) = state_clone.db.record_source_sync_started(source_id).await {
+                error!("Failed to record the start of the sync of source {}: {}", source.name, e);
+            }
+            let started = std::time::Instant::now();
+            
+            // Execute the sync operation with a timeout to prevent hanging; the source's
+            // max runtime replaces the default of 5 minutes
             let sync_result = tokio::time::timeout(
-                std::time::Duration::from_secs(300), // 5 minute timeout for sync operations
+                max_runtime.unwrap_or(std::time::Duration::from_secs(300)),
                 sync_service.sync_source_with_cancellation(&source, enable_background_ocr, cancellation_token)
             ).await;
             
+            if let Err(e) = state_clone.db.record_source_sync_duration(source_id, started.elapsed().as_millis() as i64).await {
+                error!("Failed to record the duration of the sync of source {}: {}", source.name, e);
+            }
+            
             match sync_result {
                 Ok(Ok(files_processed)) => {
                     info!("Manual sync completed for source {}: {} files processed", 
                           source.name, files_processed);
                     
+                    crate::services::event_service::EventService::new(state_clone.db.clone())
+                        .publish_best_effort(
+                            crate::models::EventType::SyncCompleted,
+                            Some(source.user_id),
+                            Some(source_id),
+                            serde_json::json!({
+                                "source_id": source_id,
+                                "source_type": source.source_type.to_string(),
+                                "files_processed": files_processed,
+                                "finished_at": chrono::Utc::now(),
+                            }),
+                        )
+                        .await;
+                    
                     // Atomically complete the sync
                     if let Err(e) = state_clone.db.complete_sync_atomic(
                         source_id, 
@@ -406,6 +498,19 @@ impl SourceScheduler {
                 Ok(Err(e)) => {
                     error!("Manual sync failed for source {}: {}", source.name, e);
                     
+                    crate::services::event_service::EventService::new(state_clone.db.clone())
+                        .publish_best_effort(
+                            crate::models::EventType::SyncFailed,
+                            Some(source.user_id),
+                            Some(source_id),
+                            serde_json::json!({
+                                "source_id": source_id,
+                                "source_type": source.source_type.to_string(),
+                                "error": e.to_string(),
+                            }),
+                        )
+                        .await;
+                    
                     // Atomically mark sync as failed
                     if let Err(complete_err) = state_clone.db.complete_sync_atomic(
                         source_id, 
@@ -562,6 +667,34 @@ impl SourceScheduler {
                     .map_err(|e| format!("Failed to parse Local Folder configuration JSON: {}", e))?;
                 Ok(())
             }
+            SourceType::Imap => {
+                let config: ImapSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Failed to parse IMAP configuration JSON: {}", e))?;
+                crate::services::imap_service::ImapService::new(config)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            SourceType::Dropbox => {
+                let config: DropboxSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Failed to parse Dropbox configuration JSON: {}", e))?;
+                crate::services::dropbox_service::DropboxService::new(config)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            SourceType::OneDrive => {
+                let config: OneDriveSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Failed to parse OneDrive configuration JSON: {}", e))?;
+                crate::services::onedrive_service::OneDriveService::new(config)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            SourceType::Sftp => {
+                let config: SftpSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Failed to parse SFTP configuration JSON: {}", e))?;
+                crate::services::sftp_service::SftpService::new(config)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
         }
     }
 
@@ -715,10 +848,8 @@ impl SourceScheduler {
             info!("🎯 Intelligent deep scan trigger activated for source {}: {}", source.name, reason);
             
             // Create notification about automatic deep scan
+            let locale = crate::services::i18n::locale_of(&state.db, source.user_id).await;
             let notification = crate::models::CreateNotification {
-                notification_type: "info".to_string(),
-                title: "Automatic Deep Scan Triggered".to_string(),
-                message: format!("Starting deep scan for {}: {}", source.name, reason),
                 action_url: Some("/sources".to_string()),
                 metadata: Some(serde_json::json!({
                     "source_type": source.source_type.to_string(),
@@ -727,6 +858,7 @@ impl SourceScheduler {
                     "trigger_reason": reason,
                     "automatic": true
                 })),
+                ..crate::services::i18n::notification(&locale, "info", "notification.auto_deep_scan_started", &[("source", source.name.clone()), ("reason", reason.to_string())])
             };
             
             if let Err(e) = state.db.create_notification(source.user_id, &notification).await {
@@ -817,10 +949,8 @@ impl SourceScheduler {
                         };
                         
                         // Success notification
+                        let locale = crate::services::i18n::locale_of(&state_clone.db, source_clone.user_id).await;
                         let notification = crate::models::CreateNotification {
-                            notification_type: "success".to_string(),
-                            title: "Automatic Deep Scan Completed".to_string(),
-                            message: format!("Deep scan of {} completed successfully", source_clone.name),
                             action_url: Some("/documents".to_string()),
                             metadata: Some(serde_json::json!({
                                 "source_type": source_clone.source_type.to_string(),
@@ -829,6 +959,7 @@ impl SourceScheduler {
                                 "automatic": true,
                                 "files_found": files_processed
                             })),
+                            ..crate::services::i18n::notification(&locale, "success", "notification.auto_deep_scan_completed", &[("source", source_clone.name.clone())])
                         };
                         
                         if let Err(e) = state_clone.db.create_notification(source_clone.user_id, &notification).await {
@@ -901,6 +1032,42 @@ impl SourceScheduler {
                     }));
                 }
             }
+            crate::models::SourceType::Imap => {
+                if let Err(e) = Self::validate_imap_connectivity(source).await {
+                    validation_score -= 25;
+                    if validation_status == "healthy" { validation_status = "warning"; }
+                    validation_issues.push(serde_json::json!({
+                        "type": "connectivity",
+                        "severity": "warning",
+                        "message": format!("IMAP connectivity issue: {}", e),
+                        "recommendation": "Check server, port, credentials and mailbox name"
+                    }));
+                }
+            }
+            crate::models::SourceType::Dropbox | crate::models::SourceType::OneDrive => {
+                if let Err(e) = Self::validate_cloud_drive_connectivity(source).await {
+                    validation_score -= 25;
+                    if validation_status == "healthy" { validation_status = "warning"; }
+                    validation_issues.push(serde_json::json!({
+                        "type": "connectivity",
+                        "severity": "warning",
+                        "message": format!("{} connectivity issue: {}", source.source_type, e),
+                        "recommendation": "Check the app credentials and that the refresh token has not been revoked"
+                    }));
+                }
+            }
+            crate::models::SourceType::Sftp => {
+                if let Err(e) = Self::validate_sftp_connectivity(source).await {
+                    validation_score -= 25;
+                    if validation_status == "healthy" { validation_status = "warning"; }
+                    validation_issues.push(serde_json::json!({
+                        "type": "connectivity",
+                        "severity": "warning",
+                        "message": format!("SFTP connectivity issue: {}", e),
+                        "recommendation": "Check host, port, credentials, host key fingerprint and remote path"
+                    }));
+                }
+            }
         }
 
         // 3. Sync pattern analysis
@@ -954,10 +1121,13 @@ impl SourceScheduler {
 
         // Send notification if there are critical issues
         if validation_status == "critical" || validation_score < 50 {
+            let (notification_type, key) = if validation_status == "critical" {
+                ("error", "notification.source_validation_failed")
+            } else {
+                ("warning", "notification.source_validation_warning")
+            };
+            let locale = crate::services::i18n::locale_of(&state.db, source.user_id).await;
             let notification = crate::models::CreateNotification {
-                notification_type: if validation_status == "critical" { "error" } else { "warning" }.to_string(),
-                title: format!("Source Validation {}", if validation_status == "critical" { "Failed" } else { "Warning" }),
-                message: format!("Source {} has validation issues (score: {})", source.name, validation_score),
                 action_url: Some("/sources".to_string()),
                 metadata: Some(serde_json::json!({
                     "source_type": source.source_type.to_string(),
@@ -967,6 +1137,7 @@ impl SourceScheduler {
                     "validation_status": validation_status,
                     "issue_count": validation_issues.len()
                 })),
+                ..crate::services::i18n::notification(&locale, notification_type, key, &[("source", source.name.clone()), ("score", validation_score.to_string())])
             };
 
             if let Err(e) = state.db.create_notification(source.user_id, &notification).await {
@@ -1015,6 +1186,34 @@ impl SourceScheduler {
                     .map_err(|e| format!("Failed to parse Local Folder configuration: {}", e))?;
                 Ok(())
             }
+            SourceType::Imap => {
+                let config: ImapSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Failed to parse IMAP configuration: {}", e))?;
+                crate::services::imap_service::ImapService::new(config)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            SourceType::Dropbox => {
+                let config: DropboxSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Failed to parse Dropbox configuration: {}", e))?;
+                crate::services::dropbox_service::DropboxService::new(config)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            SourceType::OneDrive => {
+                let config: OneDriveSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Failed to parse OneDrive configuration: {}", e))?;
+                crate::services::onedrive_service::OneDriveService::new(config)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            SourceType::Sftp => {
+                let config: SftpSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Failed to parse SFTP configuration: {}", e))?;
+                crate::services::sftp_service::SftpService::new(config)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
         }
     }
 
@@ -1056,6 +1255,58 @@ impl SourceScheduler {
         Ok(())
     }
 
+    async fn validate_imap_connectivity(source: &crate::models::Source) -> Result<(), String> {
+        let config: ImapSourceConfig = serde_json::from_value(source.config.clone())
+            .map_err(|e| format!("Config parse error: {}", e))?;
+
+        crate::services::imap_service::ImapService::new(config)
+            .map_err(|e| e.to_string())?
+            .test_connection()
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Connection test failed: {}", e))
+    }
+
+    async fn validate_sftp_connectivity(source: &crate::models::Source) -> Result<(), String> {
+        let config: SftpSourceConfig = serde_json::from_value(source.config.clone())
+            .map_err(|e| format!("Config parse error: {}", e))?;
+
+        crate::services::sftp_service::SftpService::new(config)
+            .map_err(|e| e.to_string())?
+            .test_connection()
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Connection test failed: {}", e))
+    }
+
+    async fn validate_cloud_drive_connectivity(source: &crate::models::Source) -> Result<(), String> {
+        use crate::models::SourceType;
+        use crate::services::cloud_drive::{self, CloudDriveClient};
+
+        let (client, refresh_token): (Box<dyn CloudDriveClient>, String) = match source.source_type {
+            SourceType::Dropbox => {
+                let config: DropboxSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Config parse error: {}", e))?;
+                let refresh_token = config.refresh_token.clone();
+                let client = crate::services::dropbox_service::DropboxService::new(config).map_err(|e| e.to_string())?;
+                (Box::new(client), refresh_token)
+            }
+            SourceType::OneDrive => {
+                let config: OneDriveSourceConfig = serde_json::from_value(source.config.clone())
+                    .map_err(|e| format!("Config parse error: {}", e))?;
+                let refresh_token = config.refresh_token.clone();
+                let client = crate::services::onedrive_service::OneDriveService::new(config).map_err(|e| e.to_string())?;
+                (Box::new(client), refresh_token)
+            }
+            _ => return Err(format!("{} is not a cloud drive source", source.source_type)),
+        };
+
+        cloud_drive::test_connection(client.as_ref(), &refresh_token)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Connection test failed: {}", e))
+    }
+
     async fn validate_s3_connectivity(_source: &crate::models::Source) -> Result<(), String> {
         // Simplified S3 validation - could be enhanced with actual AWS SDK calls
         // For now, just return OK as S3 validation requires more complex setup
@@ -1,19 +1,30 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::path::Path;
 use anyhow::{anyhow, Result};
 use tokio::sync::Semaphore;
 use tokio_util::sync::CancellationToken;
 use futures::stream::{FuturesUnordered, StreamExt};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use crate::{
     AppState,
-    models::{FileIngestionInfo, Source, SourceType, SourceStatus, LocalFolderSourceConfig, S3SourceConfig, WebDAVSourceConfig},
-    ingestion::document_ingestion::{DocumentIngestionService, IngestionResult},
+    db::workspaces::WorkspaceScoped,
+    scheduling::sync_runs::{self, SyncRunRecorder},
+    models::{FileIngestionInfo, Source, SourceType, SourceStatus, LocalFolderSourceConfig, S3SourceConfig, WebDAVSourceConfig, WebDAVSyncDirection, ImapSourceConfig, DropboxSourceConfig, OneDriveSourceConfig, SftpSourceConfig, RemoteProcessedAction},
+    ingestion::document_ingestion::{DeduplicationPolicy, DocumentIngestionRequest, DocumentIngestionService, IngestionResult},
+    ocr::email_parser,
+    services::cloud_drive::{self, CloudDriveClient, CloudFile, OAuthToken, PathFilter},
+    services::dropbox_service::DropboxService,
+    services::imap_service::{self, ImapService},
     services::local_folder_service::LocalFolderService,
+    services::onedrive_service::OneDriveService,
     services::s3_service::S3Service,
-    services::webdav::{WebDAVService, WebDAVConfig, SyncProgress, SyncPhase},
+    services::sftp_service::{RemoteFile, SftpService},
+    services::storage_quota_service,
+    services::workspace_service,
+    services::webdav::{WebDAVService, WebDAVConfig, SyncProgress, SyncPhase, write_back},
 };
 
 #[derive(Clone)]
@@ -33,6 +44,7 @@ impl SourceSyncService {
     }
 
     /// Perform sync for any source type with cancellation support
+    #[tracing::instrument(name = "source.sync", skip_all, fields(source_id = %source.id, source_type = %source.source_type))]
     pub async fn sync_source_with_cancellation(&self, source: &Source, enable_background_ocr: bool, cancellation_token: CancellationToken) -> Result<usize> {
         info!("Starting sync for source {} ({})", source.name, source.source_type);
 
@@ -47,11 +59,67 @@ impl SourceSyncService {
             error!("Failed to update source status: {}", e);
         }
 
-        let sync_result = match source.source_type {
-            SourceType::WebDAV => self.sync_webdav_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
-            SourceType::LocalFolder => self.sync_local_folder_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
-            SourceType::S3 => self.sync_s3_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
+        let run_id = match self.state.db.start_source_sync_run(source.id).await {
+            Ok(run_id) => Some(run_id),
+            Err(e) => {
+                error!("Failed to add the sync of source {} to its history: {}", source.id, e);
+                None
+            }
         };
+        let recorder = Arc::new(SyncRunRecorder::default());
+
+        let started = std::time::Instant::now();
+        let sync_result = recorder.record(async {
+            // Nothing can be stored for an owner whose quota is used up
+            let usage = storage_quota_service::StorageQuotaService::new(self.state.db.clone()).usage(source.user_id).await?;
+            if usage.remaining_bytes == Some(0) {
+                return Err(anyhow!(
+                    "Storage quota exceeded: {} of {} used",
+                    storage_quota_service::format_mb(usage.used_bytes),
+                    storage_quota_service::format_mb(usage.quota_bytes.unwrap_or_default())
+                ));
+            }
+
+            // Files of a workspace's source are sealed with the workspace's key
+            let workspace_id = self.state.db.get_workspace_of(WorkspaceScoped::Source, source.id).await?;
+            workspace_service::within(workspace_id, async {
+                match source.source_type {
+                    SourceType::WebDAV => self.sync_webdav_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
+                    SourceType::LocalFolder => self.sync_local_folder_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
+                    SourceType::S3 => self.sync_s3_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
+                    SourceType::Imap => self.sync_imap_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
+                    SourceType::Dropbox => self.sync_dropbox_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
+                    SourceType::OneDrive => self.sync_onedrive_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
+                    SourceType::Sftp => self.sync_sftp_source_with_cancellation(source, enable_background_ocr, cancellation_token.clone()).await,
+                }
+            }).await
+        }).await;
+
+        let outcome = match &sync_result {
+            _ if cancellation_token.is_cancelled() => recorder.outcome(sync_runs::STATUS_CANCELLED, None),
+            Ok(_) => recorder.outcome(sync_runs::STATUS_SUCCEEDED, None),
+            Err(e) => recorder.outcome(sync_runs::STATUS_FAILED, Some(e.to_string())),
+        };
+        crate::monitoring::prometheus::record_sync_run(&source.source_type.to_string(), &outcome);
+        if let Some(run_id) = run_id {
+            if let Err(e) = self.state.db.finish_source_sync_run(run_id, &outcome).await {
+                error!("Failed to record the outcome of sync run {}: {}", run_id, e);
+            }
+        }
+
+        crate::monitoring::slow_operations::record(
+            crate::monitoring::slow_operations::OperationCategory::Sync,
+            "sync.source",
+            started,
+            sync_result.is_ok(),
+            serde_json::json!({
+                "source_id": source.id,
+                "source_name": source.name,
+                "source_type": source.source_type.to_string(),
+                "files_processed": sync_result.as_ref().ok(),
+                "cancelled": cancellation_token.is_cancelled(),
+            }),
+        );
 
         match &sync_result {
             Ok(files_processed) => {
@@ -116,6 +184,15 @@ impl SourceSyncService {
 
         info!("WebDAV service created successfully, starting sync with {} folders", webdav_config.watch_folders.len());
 
+        // Deletes and renames made in Readur go to the server before anything is pulled
+        if config.sync_direction == WebDAVSyncDirection::Bidirectional {
+            match write_back::apply_pending_changes(&self.state.db, source.id, &webdav_service).await {
+                Ok(summary) => info!("WebDAV write-back for source '{}': {} applied, {} conflicts, {} failed",
+                                     source.name, summary.applied, summary.conflicts, summary.failed),
+                Err(e) => error!("WebDAV write-back for source '{}' failed: {}", source.name, e),
+            }
+        }
+
         // Create progress tracker for scheduled sync and register it globally
         let progress = Arc::new(SyncProgress::new());
         progress.set_phase(SyncPhase::Initializing);
@@ -145,7 +222,7 @@ impl SourceSyncService {
                         Ok(Some(sync_result)) => {
                             info!("✅ Smart sync completed for {}: {} files found using {:?}", 
                                   folder_path, sync_result.files.len(), sync_result.strategy_used);
-                            Ok(sync_result.files)
+                            Ok(sync_result.files.into_iter().map(write_back::with_etag_metadata).collect())
                         },
                         Ok(None) => {
                             info!("🔍 Smart sync: No changes detected for {}, skipping", folder_path);
@@ -254,6 +331,14 @@ impl SourceSyncService {
         self.state.sync_progress_tracker.register_sync(source.id, progress.clone());
         info!("🚀 Starting S3 sync with progress tracking for source '{}'", source.name);
 
+        // Objects whose ETag matches the one recorded at their last ingestion are not downloaded again
+        let seen_etags = Arc::new(self.state.db.get_s3_object_etags(source.id).await.unwrap_or_else(|e| {
+            error!("Failed to load ingested S3 objects for source {}: {}", source.id, e);
+            HashMap::new()
+        }));
+        let listed = Arc::new(std::sync::Mutex::new(HashMap::new()));
+        let sync_started_at = chrono::Utc::now();
+
         let sync_result = self.perform_sync_internal_with_cancellation(
             source.user_id,
             source.id,
@@ -263,13 +348,30 @@ impl SourceSyncService {
             cancellation_token,
             |folder_path| {
                 let service = s3_service.clone();
-                async move { service.discover_files_in_folder(&folder_path).await }
+                let seen_etags = seen_etags.clone();
+                let listed = listed.clone();
+                async move {
+                    let files = service.discover_files_in_folder(&folder_path).await?;
+                    let changed: Vec<FileIngestionInfo> = files
+                        .into_iter()
+                        .filter(|f| seen_etags.get(&f.relative_path) != Some(&f.etag))
+                        .collect();
+                    if let Ok(mut listed) = listed.lock() {
+                        listed.extend(changed.iter().map(|f| (f.relative_path.clone(), f.etag.clone())));
+                    }
+                    Ok::<_, anyhow::Error>(changed)
+                }
             },
             |file_path| {
                 let service = s3_service.clone();
                 async move { service.download_file(&file_path).await }
             }
         ).await;
+
+        let listed: Vec<(String, String)> = listed.lock().map(|l| l.clone().into_iter().collect()).unwrap_or_default();
+        if let Err(e) = self.state.db.record_s3_objects_from_sync(source.id, &listed, sync_started_at).await {
+            error!("Failed to record ingested S3 objects for source {}: {}", source.id, e);
+        }
         
         // Always mark sync phase and unregister progress tracker, regardless of result
         match &sync_result {
@@ -283,6 +385,490 @@ impl SourceSyncService {
         sync_result
     }
 
+    async fn sync_imap_source_with_cancellation(&self, source: &Source, enable_background_ocr: bool, cancellation_token: CancellationToken) -> Result<usize> {
+        let config: ImapSourceConfig = serde_json::from_value(source.config.clone())
+            .map_err(|e| anyhow!("Invalid IMAP config: {}", e))?;
+
+        let imap_service = ImapService::new(config.clone())?;
+        let mut mailbox = imap_service.connect().await?;
+        let messages = mailbox.fetch_matching().await?;
+        info!("Found {} matching messages in '{}' for source '{}'", messages.len(), config.mailbox, source.name);
+
+        let mut files_processed = 0;
+        let mut processed_uids = Vec::with_capacity(messages.len());
+        for (uid, raw) in messages {
+            if cancellation_token.is_cancelled() {
+                info!("IMAP sync for source {} cancelled", source.name);
+                break;
+            }
+
+            // A message that fails stays unprocessed in the mailbox and is retried on the next sync
+            match self.ingest_imap_message(source, &config, uid, &raw, enable_background_ocr).await {
+                Ok(count) => {
+                    files_processed += count;
+                    processed_uids.push(uid);
+                }
+                Err(e) => {
+                    error!("Failed to ingest message {} from '{}': {}", uid, config.mailbox, e);
+                    sync_runs::record_failure(&format!("{}/{}", config.mailbox, uid), &e);
+                }
+            }
+        }
+
+        let marked = mailbox.mark_processed(&processed_uids).await;
+        mailbox.logout().await;
+        marked.map_err(|e| anyhow!("Failed to mark {} processed messages: {}", processed_uids.len(), e))?;
+
+        Ok(files_processed)
+    }
+
+    /// Ingest the wanted attachments of one message and label them by the source's rules.
+    /// Returns the number of new documents.
+    async fn ingest_imap_message(
+        &self,
+        source: &Source,
+        config: &ImapSourceConfig,
+        uid: u32,
+        raw: &[u8],
+        enable_background_ocr: bool,
+    ) -> Result<usize> {
+        let email = email_parser::parse_email(raw)?;
+        let labels = imap_service::matching_labels(&config.label_rules, email.from.as_deref(), email.subject.as_deref());
+        let ingestion_service = DocumentIngestionService::new(self.state.db.clone(), (*self.state.file_service).clone());
+
+        let mut created = 0;
+        for attachment in email.attachments {
+            let extension = Path::new(&attachment.filename)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("")
+                .to_lowercase();
+            if !config.file_extensions.is_empty() && !config.file_extensions.iter().any(|e| e.eq_ignore_ascii_case(&extension)) {
+                debug!("Skipping attachment {} of message {}: extension not selected", attachment.filename, uid);
+                continue;
+            }
+
+            let file_size = attachment.data.len() as i64;
+            let request = DocumentIngestionRequest {
+                filename: attachment.filename.clone(),
+                original_filename: attachment.filename.clone(),
+                file_data: attachment.data,
+                mime_type: attachment.mime_type,
+                user_id: source.user_id,
+                deduplication_policy: DeduplicationPolicy::Skip,
+                source_type: Some("source_sync".to_string()),
+                source_id: Some(source.id),
+                original_created_at: email.date,
+                original_modified_at: email.date,
+                source_path: Some(format!("{}/{}/{}", config.mailbox, uid, attachment.filename)),
+                file_permissions: None,
+                file_owner: None,
+                file_group: None,
+                source_metadata: Some(serde_json::json!({
+                    "email_message_id": email.message_id,
+                    "email_subject": email.subject,
+                    "email_from": email.from,
+                })),
+            };
+
+            let result = ingestion_service.ingest_document(request).await;
+            if let Ok(result) = &result {
+                sync_runs::record_ingestion(result);
+            }
+            let document = match result {
+                Ok(IngestionResult::Created(document)) => document,
+                Ok(other) => {
+                    debug!("Attachment {} of message {} not ingested: {:?}", attachment.filename, uid, other);
+                    continue;
+                }
+                Err(e) => return Err(anyhow!("Document ingestion failed for {}: {}", attachment.filename, e)),
+            };
+
+            for label in &labels {
+                if let Err(e) = self.state.db.assign_label_by_name(source.user_id, document.id, label).await {
+                    error!("Failed to apply label '{}' to document {}: {}", label, document.id, e);
+                }
+            }
+
+            if enable_background_ocr {
+                if let Err(e) = self.state.queue_service.enqueue_document(document.id, 8, file_size).await {
+                    error!("Failed to enqueue document for OCR: {}", e);
+                }
+            }
+            created += 1;
+        }
+
+        Ok(created)
+    }
+
+    async fn sync_dropbox_source_with_cancellation(&self, source: &Source, enable_background_ocr: bool, cancellation_token: CancellationToken) -> Result<usize> {
+        let config: DropboxSourceConfig = serde_json::from_value(source.config.clone())
+            .map_err(|e| anyhow!("Invalid Dropbox config: {}", e))?;
+        let filter = PathFilter::new(&config.include_patterns, &config.exclude_patterns)?;
+        let refresh_token = config.refresh_token.clone();
+        let client = DropboxService::new(config)?;
+
+        self.sync_cloud_drive_source(source, &client, &refresh_token, &filter, enable_background_ocr, cancellation_token).await
+    }
+
+    async fn sync_onedrive_source_with_cancellation(&self, source: &Source, enable_background_ocr: bool, cancellation_token: CancellationToken) -> Result<usize> {
+        let config: OneDriveSourceConfig = serde_json::from_value(source.config.clone())
+            .map_err(|e| anyhow!("Invalid OneDrive config: {}", e))?;
+        let filter = PathFilter::new(&config.include_patterns, &config.exclude_patterns)?;
+        let refresh_token = config.refresh_token.clone();
+        let client = OneDriveService::new(config)?;
+
+        self.sync_cloud_drive_source(source, &client, &refresh_token, &filter, enable_background_ocr, cancellation_token).await
+    }
+
+    /// Ingest the changes a cloud drive reports since the stored delta cursor. The cursor
+    /// only advances past pages whose files were all ingested, so a file that failed is
+    /// fetched again by the next sync.
+    async fn sync_cloud_drive_source(
+        &self,
+        source: &Source,
+        client: &dyn CloudDriveClient,
+        configured_refresh_token: &str,
+        filter: &PathFilter,
+        enable_background_ocr: bool,
+        cancellation_token: CancellationToken,
+    ) -> Result<usize> {
+        let provider = client.provider_name();
+        let sync_state = self.state.db.get_source_sync_state(source.id).await?;
+
+        let mut stored_token = None;
+        if let Some(state) = &sync_state {
+            if let (Some(access_token), Some(expires_at)) = (&state.access_token, state.access_token_expires_at) {
+                if cloud_drive::token_is_fresh(expires_at) {
+                    stored_token = self.open_token(access_token).await.ok().map(|access_token| OAuthToken {
+                        access_token,
+                        expires_at,
+                        refresh_token: None,
+                    });
+                }
+            }
+        }
+        let mut token = match stored_token {
+            Some(token) => token,
+            None => self.refresh_cloud_token(source, client, configured_refresh_token).await?,
+        };
+
+        let mut cursor = sync_state.and_then(|state| state.delta_cursor);
+        if cursor.is_none() {
+            info!("No {} delta cursor for source '{}' yet, listing the whole folder", provider, source.name);
+        }
+
+        let ingestion_service = DocumentIngestionService::new(self.state.db.clone(), (*self.state.file_service).clone());
+        let mut files_processed = 0;
+        let mut retried_unauthorized = false;
+        loop {
+            if cancellation_token.is_cancelled() {
+                info!("{} sync for source {} cancelled", provider, source.name);
+                break;
+            }
+            if !cloud_drive::token_is_fresh(token.expires_at) {
+                token = self.refresh_cloud_token(source, client, configured_refresh_token).await?;
+            }
+
+            let page = match client.delta(&token.access_token, cursor.as_deref()).await {
+                Ok(page) => page,
+                Err(e) if cloud_drive::is_unauthorized(&e) && !retried_unauthorized => {
+                    // The token was revoked or expired early; refresh once and retry
+                    retried_unauthorized = true;
+                    token = self.refresh_cloud_token(source, client, configured_refresh_token).await?;
+                    continue;
+                }
+                Err(e) if cloud_drive::is_cursor_expired(&e) && cursor.is_some() => {
+                    warn!("{} delta cursor of source '{}' expired, starting over with a full listing", provider, source.name);
+                    self.state.db.save_source_delta_cursor(source.id, None).await?;
+                    cursor = None;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            retried_unauthorized = false;
+
+            if page.deleted_count > 0 {
+                debug!("{} reported {} deleted entries for source '{}'; their documents are kept", provider, page.deleted_count, source.name);
+            }
+
+            let mut failed = 0;
+            let mut cancelled = false;
+            for file in page.files.iter().filter(|file| filter.matches(&file.relative_path)) {
+                if cancellation_token.is_cancelled() {
+                    cancelled = true;
+                    break;
+                }
+                if !cloud_drive::token_is_fresh(token.expires_at) {
+                    token = self.refresh_cloud_token(source, client, configured_refresh_token).await?;
+                }
+
+                let file_data = match client.download(&token.access_token, file).await {
+                    Err(e) if cloud_drive::is_unauthorized(&e) => {
+                        token = self.refresh_cloud_token(source, client, configured_refresh_token).await?;
+                        client.download(&token.access_token, file).await
+                    }
+                    result => result,
+                };
+                let result = match file_data {
+                    Ok(file_data) => {
+                        self.ingest_cloud_file(&ingestion_service, source, file, file_data, enable_background_ocr).await
+                    }
+                    Err(e) => Err(anyhow!("Failed to download {}: {}", file.display_path, e)),
+                };
+                match result {
+                    Ok(true) => files_processed += 1,
+                    Ok(false) => {}
+                    Err(e) => {
+                        failed += 1;
+                        error!("{} sync of source '{}': {}", provider, source.name, e);
+                        sync_runs::record_failure(&file.display_path, &e);
+                    }
+                }
+            }
+
+            // Keep the cursor before this page so the rest of it is fetched again
+            if cancelled {
+                info!("{} sync for source {} cancelled", provider, source.name);
+                break;
+            }
+            if failed > 0 {
+                return Err(anyhow!(
+                    "{} changed {} files could not be ingested and will be retried on the next sync",
+                    failed, provider
+                ));
+            }
+
+            self.state.db.save_source_delta_cursor(source.id, Some(&page.cursor)).await?;
+            cursor = Some(page.cursor);
+            if !page.has_more {
+                break;
+            }
+        }
+
+        Ok(files_processed)
+    }
+
+    /// Exchange a refresh token for a new access token and store both. A refresh token
+    /// the provider rotated during an earlier sync is tried before the configured one.
+    async fn refresh_cloud_token(&self, source: &Source, client: &dyn CloudDriveClient, configured_refresh_token: &str) -> Result<OAuthToken> {
+        let rotated_refresh_token = match self.state.db.get_source_sync_state(source.id).await?.and_then(|state| state.refresh_token) {
+            Some(stored) => Some(self.open_token(&stored).await?).filter(|refresh_token| refresh_token != configured_refresh_token),
+            None => None,
+        };
+
+        let token = match rotated_refresh_token {
+            Some(rotated) => match client.refresh_access_token(&rotated).await {
+                Ok(token) => token,
+                Err(e) => {
+                    warn!("Stored {} refresh token was rejected, falling back to the configured one: {}", client.provider_name(), e);
+                    client.refresh_access_token(configured_refresh_token).await?
+                }
+            },
+            None => client.refresh_access_token(configured_refresh_token).await?,
+        };
+
+        let access_token = self.seal_token(source, &token.access_token).await?;
+        let refresh_token = match &token.refresh_token {
+            Some(refresh_token) => Some(self.seal_token(source, refresh_token).await?),
+            None => None,
+        };
+        self.state.db
+            .save_source_tokens(source.id, &access_token, token.expires_at, refresh_token.as_deref())
+            .await?;
+        Ok(token)
+    }
+
+    /// Stored OAuth tokens are encrypted with the key of the source's workspace or owner
+    /// when encryption is enabled
+    async fn seal_token(&self, source: &Source, token: &str) -> Result<String> {
+        match self.state.file_service.encryption() {
+            Some(encryption) => {
+                let owner = encryption.key_owner(source.user_id, Some((WorkspaceScoped::Source, source.id))).await?;
+                encryption.encrypt_field(owner, token).await
+            }
+            None => Ok(token.to_string()),
+        }
+    }
+
+    async fn open_token(&self, token: &str) -> Result<String> {
+        match self.state.file_service.encryption() {
+            Some(encryption) => encryption.decrypt_field(token).await,
+            None => Ok(token.to_string()),
+        }
+    }
+
+    /// Returns whether a new document was created
+    async fn ingest_cloud_file(
+        &self,
+        ingestion_service: &DocumentIngestionService,
+        source: &Source,
+        file: &CloudFile,
+        file_data: Vec<u8>,
+        enable_background_ocr: bool,
+    ) -> Result<bool> {
+        let request = DocumentIngestionRequest {
+            filename: file.name.clone(),
+            original_filename: file.name.clone(),
+            file_data,
+            mime_type: mime_guess::from_path(&file.name).first_or_octet_stream().to_string(),
+            user_id: source.user_id,
+            deduplication_policy: DeduplicationPolicy::Skip,
+            source_type: Some("source_sync".to_string()),
+            source_id: Some(source.id),
+            original_created_at: None,
+            original_modified_at: file.modified_at,
+            source_path: Some(file.display_path.clone()),
+            file_permissions: None,
+            file_owner: None,
+            file_group: None,
+            source_metadata: Some(serde_json::json!({
+                "provider": source.source_type.to_string(),
+                "file_id": file.id,
+                "content_hash": file.content_hash,
+            })),
+        };
+
+        let result = ingestion_service.ingest_document(request).await;
+        if let Ok(result) = &result {
+            sync_runs::record_ingestion(result);
+        }
+        let document = match result {
+            Ok(IngestionResult::Created(document)) | Ok(IngestionResult::NewVersion(document)) => document,
+            Ok(other) => {
+                debug!("{} not ingested: {:?}", file.display_path, other);
+                return Ok(false);
+            }
+            Err(e) => return Err(anyhow!("Document ingestion failed for {}: {}", file.display_path, e)),
+        };
+
+        if enable_background_ocr {
+            if let Err(e) = self.state.queue_service.enqueue_document(document.id, 8, file.size).await {
+                error!("Failed to enqueue document for OCR: {}", e);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Ingest the settled files of an SFTP/FTP directory. A file is only deleted or moved
+    /// once it is in Readur; kept files are downloaded again only when they change.
+    async fn sync_sftp_source_with_cancellation(&self, source: &Source, enable_background_ocr: bool, cancellation_token: CancellationToken) -> Result<usize> {
+        let config: SftpSourceConfig = serde_json::from_value(source.config.clone())
+            .map_err(|e| anyhow!("Invalid SFTP config: {}", e))?;
+
+        let sftp_service = SftpService::new(config.clone())?;
+        let connection = sftp_service.connect().await?;
+        let files = match connection.list_files().await {
+            Ok(files) => files,
+            Err(e) => {
+                connection.close().await;
+                return Err(e);
+            }
+        };
+        let known_files = if config.processed_action == RemoteProcessedAction::Keep {
+            self.state.db.get_remote_source_files(source.id).await?
+        } else {
+            HashMap::new()
+        };
+        info!("Found {} files in {} for source '{}'", files.len(), config.remote_path, source.name);
+
+        let ingestion_service = DocumentIngestionService::new(self.state.db.clone(), (*self.state.file_service).clone());
+        let mut files_processed = 0;
+        let mut failed = 0;
+        for file in files {
+            if cancellation_token.is_cancelled() {
+                info!("SFTP sync for source {} cancelled", source.name);
+                break;
+            }
+            let signature = (file.size as i64, file.modified_at);
+            if known_files.get(&file.path) == Some(&signature) {
+                continue;
+            }
+
+            let ingested = match connection.download(&file).await {
+                Ok(file_data) => self.ingest_remote_file(&ingestion_service, source, &file, file_data, enable_background_ocr).await,
+                Err(e) => Err(anyhow!("Failed to download {}: {}", file.path, e)),
+            };
+            match ingested {
+                Ok(created) => {
+                    if created {
+                        files_processed += 1;
+                    }
+                    let finished = match config.processed_action {
+                        RemoteProcessedAction::Keep => {
+                            self.state.db.record_remote_source_file(source.id, &file.path, signature.0, signature.1).await
+                        }
+                        RemoteProcessedAction::Delete | RemoteProcessedAction::Move => connection.mark_processed(&file).await,
+                    };
+                    if let Err(e) = finished {
+                        error!("Failed to mark {} processed for source '{}': {}", file.path, source.name, e);
+                    }
+                }
+                Err(e) => {
+                    failed += 1;
+                    error!("SFTP sync of source '{}': {}", source.name, e);
+                    sync_runs::record_failure(&file.path, &e);
+                }
+            }
+        }
+        connection.close().await;
+
+        if failed > 0 {
+            return Err(anyhow!("{} files could not be ingested and will be retried on the next sync", failed));
+        }
+        Ok(files_processed)
+    }
+
+    /// Returns whether a new document was created
+    async fn ingest_remote_file(
+        &self,
+        ingestion_service: &DocumentIngestionService,
+        source: &Source,
+        file: &RemoteFile,
+        file_data: Vec<u8>,
+        enable_background_ocr: bool,
+    ) -> Result<bool> {
+        let request = DocumentIngestionRequest {
+            filename: file.name.clone(),
+            original_filename: file.name.clone(),
+            file_data,
+            mime_type: mime_guess::from_path(&file.name).first_or_octet_stream().to_string(),
+            user_id: source.user_id,
+            deduplication_policy: DeduplicationPolicy::Skip,
+            source_type: Some("source_sync".to_string()),
+            source_id: Some(source.id),
+            original_created_at: None,
+            original_modified_at: file.modified_at,
+            source_path: Some(file.path.clone()),
+            file_permissions: None,
+            file_owner: None,
+            file_group: None,
+            source_metadata: None,
+        };
+
+        let result = ingestion_service.ingest_document(request).await;
+        if let Ok(result) = &result {
+            sync_runs::record_ingestion(result);
+        }
+        let document = match result {
+            Ok(IngestionResult::Created(document)) | Ok(IngestionResult::NewVersion(document)) => document,
+            Ok(other) => {
+                debug!("{} not ingested: {:?}", file.path, other);
+                return Ok(false);
+            }
+            Err(e) => return Err(anyhow!("Document ingestion failed for {}: {}", file.path, e)),
+        };
+
+        if enable_background_ocr {
+            if let Err(e) = self.state.queue_service.enqueue_document(document.id, 8, file.size as i64).await {
+                error!("Failed to enqueue document for OCR: {}", e);
+            }
+        }
+
+        Ok(true)
+    }
+
     async fn perform_sync_internal<F, D, Fut1, Fut2>(
         &self,
         user_id: Uuid,
@@ -508,7 +1094,7 @@ impl SourceSyncService {
                         let cancellation_token_clone = cancellation_token.clone();
 
                         let future = async move {
-                            Self::process_single_file_with_cancellation(
+                            let result = Self::process_single_file_with_cancellation(
                                 state_clone,
                                 user_id,
                                 source_id,
@@ -517,14 +1103,15 @@ impl SourceSyncService {
                                 semaphore_clone,
                                 download_file_clone,
                                 cancellation_token_clone,
-                            ).await
+                            ).await;
+                            (file_info_clone.relative_path, result)
                         };
 
                         file_futures.push(future);
                     }
 
                     // Process files concurrently and update stats periodically
-                    while let Some(result) = file_futures.next().await {
+                    while let Some((file_path, result)) = file_futures.next().await {
                         // Check for cancellation during processing
                         if cancellation_token.is_cancelled() {
                             info!("Sync cancelled during concurrent file processing");
@@ -555,12 +1142,14 @@ impl SourceSyncService {
                             }
                             Err(error) => {
                                 error!("File processing error: {}", error);
+                                sync_runs::record_failure(&file_path, &error);
                             }
                         }
                     }
                 }
                 Err(e) => {
                     error!("Failed to discover files in folder {}: {}", folder_path, e);
+                    sync_runs::record_failure(folder_path, &e);
                 }
             }
         }
@@ -624,6 +1213,10 @@ impl SourceSyncService {
                 debug!("Created new document for {}: {}", file_info.name, doc.id);
                 (doc, true) // New document - queue for OCR
             }
+            IngestionResult::NewVersion(doc) => {
+                info!("Stored changed file {} as a new version of document {}", file_info.name, doc.id);
+                (doc, true) // New contents - queue for OCR
+            }
             IngestionResult::Skipped { existing_document_id, reason } => {
                 info!("Skipped duplicate file {}: {} (existing: {})", file_info.name, reason, existing_document_id);
                 return Ok(false); // File was skipped due to deduplication
@@ -722,12 +1315,17 @@ impl SourceSyncService {
             )
             .await
             .map_err(|e| anyhow!("Document ingestion failed for {}: {}", file_info.name, e))?;
+        sync_runs::record_ingestion(&result);
 
         let (document, should_queue_ocr) = match result {
             IngestionResult::Created(doc) => {
                 debug!("Created new document for {}: {}", file_info.name, doc.id);
                 (doc, true) // New document - queue for OCR
             }
+            IngestionResult::NewVersion(doc) => {
+                info!("Stored changed file {} as a new version of document {}", file_info.name, doc.id);
+                (doc, true) // New contents - queue for OCR
+            }
             IngestionResult::Skipped { existing_document_id, reason } => {
                 info!("Skipped duplicate file {}: {} (existing: {})", file_info.name, reason, existing_document_id);
                 return Ok(false); // File was skipped due to deduplication
@@ -0,0 +1,129 @@
+//! The history of source syncs. A sync counts what happened to each file it saw in a
+//! `SyncRunRecorder` scoped to its task, and the totals are stored in `source_sync_runs`.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::ingestion::document_ingestion::IngestionResult;
+use crate::models::{SourceSyncRunOutcome, SyncRunFileError};
+
+pub const STATUS_SUCCEEDED: &str = "succeeded";
+pub const STATUS_FAILED: &str = "failed";
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+/// File errors stored per run; `files_failed` still counts every failure
+pub const MAX_FILE_ERRORS: usize = 200;
+
+/// Runs kept per source, newest first
+pub const RUNS_KEPT_PER_SOURCE: i64 = 100;
+
+tokio::task_local! {
+    static CURRENT_RUN: Arc<SyncRunRecorder>;
+}
+
+#[derive(Debug, Default)]
+pub struct SyncRunRecorder {
+    added: AtomicI32,
+    updated: AtomicI32,
+    skipped: AtomicI32,
+    failed: AtomicI32,
+    file_errors: Mutex<Vec<SyncRunFileError>>,
+}
+
+impl SyncRunRecorder {
+    /// Run a sync, counting the files recorded while it runs in this recorder
+    pub async fn record<F: Future>(self: &Arc<Self>, sync: F) -> F::Output {
+        CURRENT_RUN.scope(self.clone(), sync).await
+    }
+
+    pub fn outcome(&self, status: &'static str, error: Option<String>) -> SourceSyncRunOutcome {
+        SourceSyncRunOutcome {
+            status,
+            error,
+            files_added: self.added.load(Ordering::Relaxed),
+            files_updated: self.updated.load(Ordering::Relaxed),
+            files_skipped: self.skipped.load(Ordering::Relaxed),
+            files_failed: self.failed.load(Ordering::Relaxed),
+            file_errors: self.file_errors.lock().map(|errors| errors.clone()).unwrap_or_default(),
+        }
+    }
+
+    fn count_ingestion(&self, result: &IngestionResult) {
+        let counter = match result {
+            IngestionResult::Created(_) => &self.added,
+            IngestionResult::NewVersion(_) => &self.updated,
+            IngestionResult::Skipped { .. }
+            | IngestionResult::ExistingDocument(_)
+            | IngestionResult::TrackedAsDuplicate { .. } => &self.skipped,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn count_failure(&self, path: &str, error: String) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut errors) = self.file_errors.lock() {
+            if errors.len() < MAX_FILE_ERRORS {
+                errors.push(SyncRunFileError { path: path.to_string(), error });
+            }
+        }
+    }
+}
+
+/// Count what ingesting a file did, in the sync running on this task
+pub fn record_ingestion(result: &IngestionResult) {
+    let _ = CURRENT_RUN.try_with(|run| run.count_ingestion(result));
+}
+
+/// Count a file, or a folder, the sync running on this task could not ingest
+pub fn record_failure(path: &str, error: impl std::fmt::Display) {
+    let _ = CURRENT_RUN.try_with(|run| run.count_failure(path, error.to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_records_files_of_the_scoped_sync() {
+        let recorder = Arc::new(SyncRunRecorder::default());
+        recorder
+            .record(async {
+                record_ingestion(&IngestionResult::TrackedAsDuplicate { existing_document_id: Uuid::new_v4() });
+                record_failure("/scans/a.pdf", "Failed to download /scans/a.pdf: timed out");
+                record_failure("/scans/b.pdf", "Document ingestion failed for b.pdf: invalid PDF");
+            })
+            .await;
+        // Outside a sync nothing is recorded
+        record_failure("/scans/c.pdf", "ignored");
+
+        let outcome = recorder.outcome(STATUS_SUCCEEDED, None);
+        assert_eq!(outcome.files_skipped, 1);
+        assert_eq!(outcome.files_failed, 2);
+        assert_eq!(
+            outcome.file_errors[0],
+            SyncRunFileError {
+                path: "/scans/a.pdf".to_string(),
+                error: "Failed to download /scans/a.pdf: timed out".to_string(),
+            }
+        );
+        assert_eq!(outcome.file_errors.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_file_errors_are_capped() {
+        let recorder = Arc::new(SyncRunRecorder::default());
+        recorder
+            .record(async {
+                for i in 0..MAX_FILE_ERRORS + 5 {
+                    record_failure(&format!("/scans/{}.pdf", i), "unreadable");
+                }
+            })
+            .await;
+
+        let outcome = recorder.outcome(STATUS_FAILED, Some("5 files could not be ingested".to_string()));
+        assert_eq!(outcome.files_failed as usize, MAX_FILE_ERRORS + 5);
+        assert_eq!(outcome.file_errors.len(), MAX_FILE_ERRORS);
+    }
+}
@@ -0,0 +1,212 @@
+//! When sources sync on their own: every `sync_interval_minutes` of their config, or at
+//! the times of a cron expression, never starting in quiet hours
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+
+use super::cron::CronSchedule;
+use crate::models::{
+    DropboxSourceConfig, ImapSourceConfig, LocalFolderSourceConfig, OneDriveSourceConfig, S3SourceConfig, SftpSourceConfig,
+    Source, SourceSchedule, SourceType, WebDAVSourceConfig,
+};
+
+/// The `sync_interval_minutes` of a source's config, or None when `auto_sync` is off
+pub fn auto_sync_interval(source: &Source) -> Result<Option<i32>, serde_json::Error> {
+    let config = source.config.clone();
+    let (auto_sync, interval_minutes) = match source.source_type {
+        SourceType::WebDAV => {
+            let config: WebDAVSourceConfig = serde_json::from_value(config)?;
+            (config.auto_sync, config.sync_interval_minutes)
+        }
+        SourceType::LocalFolder => {
+            let config: LocalFolderSourceConfig = serde_json::from_value(config)?;
+            (config.auto_sync, config.sync_interval_minutes)
+        }
+        SourceType::S3 => {
+            let config: S3SourceConfig = serde_json::from_value(config)?;
+            (config.auto_sync, config.sync_interval_minutes)
+        }
+        SourceType::Imap => {
+            let config: ImapSourceConfig = serde_json::from_value(config)?;
+            (config.auto_sync, config.sync_interval_minutes)
+        }
+        SourceType::Dropbox => {
+            let config: DropboxSourceConfig = serde_json::from_value(config)?;
+            (config.auto_sync, config.sync_interval_minutes)
+        }
+        SourceType::OneDrive => {
+            let config: OneDriveSourceConfig = serde_json::from_value(config)?;
+            (config.auto_sync, config.sync_interval_minutes)
+        }
+        SourceType::Sftp => {
+            let config: SftpSourceConfig = serde_json::from_value(config)?;
+            (config.auto_sync, config.sync_interval_minutes)
+        }
+    };
+    Ok(auto_sync.then_some(interval_minutes))
+}
+
+/// When the next scheduled sync of a source starts, or None when it does not sync on
+/// its own. An overdue sync starts `now`, or when the quiet hours it falls in end.
+pub fn next_sync_at(
+    source: &Source,
+    schedule: Option<&SourceSchedule>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, String> {
+    if !source.enabled {
+        return Ok(None);
+    }
+    let Some(interval_minutes) = auto_sync_interval(source).map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+
+    let cron_expression = schedule.and_then(|schedule| schedule.cron_expression.as_deref());
+    let due = match cron_expression {
+        Some(expression) => {
+            // Cron times count from the start of the last sync, so a long sync does not skip one
+            let since = schedule
+                .and_then(|schedule| schedule.last_sync_started_at)
+                .or(source.last_sync_at)
+                .unwrap_or(source.created_at);
+            match CronSchedule::parse(expression)?.next_after(since) {
+                Some(due) => due,
+                None => return Ok(None),
+            }
+        }
+        None if interval_minutes <= 0 => {
+            return Err(format!("Invalid sync interval of {} minutes", interval_minutes));
+        }
+        None => match source.last_sync_at {
+            Some(last_sync) => last_sync + Duration::minutes(interval_minutes as i64),
+            None => now,
+        },
+    };
+
+    let due = due.max(now);
+    Ok(Some(match schedule {
+        Some(schedule) => after_quiet_hours(schedule, due),
+        None => due,
+    }))
+}
+
+/// `time`, or the end of the quiet hours it falls in
+pub fn after_quiet_hours(schedule: &SourceSchedule, time: DateTime<Utc>) -> DateTime<Utc> {
+    let (Some(start), Some(end)) = (schedule.quiet_hours_start, schedule.quiet_hours_end) else {
+        return time;
+    };
+    let time_of_day = time.time();
+    let quiet = if start <= end {
+        start <= time_of_day && time_of_day < end
+    } else {
+        time_of_day >= start || time_of_day < end
+    };
+    if !quiet {
+        return time;
+    }
+
+    let end_today = time.date_naive().and_time(end).and_utc();
+    if end_today > time {
+        end_today
+    } else {
+        end_today + Duration::days(1)
+    }
+}
+
+/// How long a sync of the source may run before it is stopped
+pub fn max_runtime(schedule: Option<&SourceSchedule>) -> Option<std::time::Duration> {
+    schedule
+        .and_then(|schedule| schedule.max_runtime_minutes)
+        .filter(|minutes| *minutes > 0)
+        .map(|minutes| std::time::Duration::from_secs(minutes as u64 * 60))
+}
+
+/// A quiet hours time as given to the API, `HH:MM`
+pub fn parse_time_of_day(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|_| format!("'{}' is not a time of day as HH:MM", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, minute, 0).unwrap()
+    }
+
+    fn source(last_sync_at: Option<DateTime<Utc>>) -> Source {
+        Source {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: "Scans".to_string(),
+            source_type: SourceType::LocalFolder,
+            enabled: true,
+            config: serde_json::json!({
+                "watch_folders": ["/scans"],
+                "file_extensions": ["pdf"],
+                "auto_sync": true,
+                "sync_interval_minutes": 60,
+                "recursive": true,
+                "follow_symlinks": false,
+            }),
+            status: crate::models::SourceStatus::Idle,
+            last_sync_at,
+            last_error: None,
+            last_error_at: None,
+            total_files_synced: 0,
+            total_files_pending: 0,
+            total_size_bytes: 0,
+            created_at: at(1, 0, 0),
+            updated_at: at(1, 0, 0),
+            validation_status: None,
+            last_validation_at: None,
+            validation_score: None,
+            validation_issues: None,
+        }
+    }
+
+    fn schedule(cron_expression: Option<&str>, quiet_hours: Option<(&str, &str)>) -> SourceSchedule {
+        SourceSchedule {
+            source_id: Uuid::new_v4(),
+            cron_expression: cron_expression.map(str::to_string),
+            quiet_hours_start: quiet_hours.map(|(start, _)| parse_time_of_day(start).unwrap()),
+            quiet_hours_end: quiet_hours.map(|(_, end)| parse_time_of_day(end).unwrap()),
+            max_runtime_minutes: None,
+            last_sync_started_at: None,
+            last_sync_duration_ms: None,
+            updated_at: at(1, 0, 0),
+        }
+    }
+
+    #[test]
+    fn test_interval_schedule() {
+        let source = source(Some(at(10, 12, 0)));
+        assert_eq!(next_sync_at(&source, None, at(10, 12, 30)), Ok(Some(at(10, 13, 0))));
+        // Overdue syncs start now
+        assert_eq!(next_sync_at(&source, None, at(10, 15, 0)), Ok(Some(at(10, 15, 0))));
+    }
+
+    #[test]
+    fn test_cron_schedule() {
+        let source = source(Some(at(10, 2, 5)));
+        let nightly = schedule(Some("0 2 * * *"), None);
+        assert_eq!(next_sync_at(&source, Some(&nightly), at(10, 12, 0)), Ok(Some(at(11, 2, 0))));
+        assert!(next_sync_at(&source, Some(&schedule(Some("0 2 *"), None)), at(10, 12, 0)).is_err());
+    }
+
+    #[test]
+    fn test_quiet_hours_defer_syncs() {
+        let source = source(Some(at(10, 21, 30)));
+        let overnight = schedule(None, Some(("22:00", "06:00")));
+        assert_eq!(next_sync_at(&source, Some(&overnight), at(10, 21, 45)), Ok(Some(at(11, 6, 0))));
+        let midday = schedule(None, Some(("12:00", "13:00")));
+        assert_eq!(next_sync_at(&source, Some(&midday), at(10, 21, 45)), Ok(Some(at(10, 22, 30))));
+    }
+
+    #[test]
+    fn test_disabled_sources_have_no_next_sync() {
+        let mut source = source(None);
+        source.enabled = false;
+        assert_eq!(next_sync_at(&source, None, at(10, 12, 0)), Ok(None));
+    }
+}
@@ -198,6 +198,10 @@ async fn start_polling_watcher(
     
     loop {
         interval.tick().await;
+        // Files dropped meanwhile are picked up by the first scan after the sync queue resumes
+        if crate::services::maintenance_service::holds(crate::models::QueueKind::Sync) {
+            continue;
+        }
         
         // Scan global watch directory
         if let Err(e) = scan_directory(&config.watch_folder, &mut known_files, &db, &file_service, &queue_service, &config, &user_watch_manager).await {
@@ -458,8 +462,8 @@ async fn process_file(
         .map_err(|e| anyhow::anyhow!(e))?;
 
     match result {
-        IngestionResult::Created(doc) => {
-            info!("Created new document for watch folder file {}: {}", file_info.name, doc.id);
+        IngestionResult::Created(doc) | IngestionResult::NewVersion(doc) => {
+            info!("Created new document or version for watch folder file {}: {}", file_info.name, doc.id);
             
             // Enqueue for OCR processing with priority based on file size and type
             let priority = calculate_priority(file_info.size, &file_info.mime_type);
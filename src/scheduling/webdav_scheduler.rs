@@ -100,15 +100,14 @@ impl WebDAVScheduler {
                                         info!("Resumed WebDAV sync completed for user {}: {} files processed", user_id, files_processed);
                                         
                                         // Send notification
+                                        let locale = crate::services::i18n::locale_of(&state_clone.db, user_id).await;
                                         let notification = crate::models::CreateNotification {
-                                            notification_type: "success".to_string(),
-                                            title: "WebDAV Sync Resumed".to_string(),
-                                            message: format!("Resumed sync after server restart. Processed {} files", files_processed),
                                             action_url: Some("/documents".to_string()),
                                             metadata: Some(serde_json::json!({
                                                 "sync_type": "webdav_resume",
                                                 "files_processed": files_processed
                                             })),
+                                            ..crate::services::i18n::notification(&locale, "success", "notification.webdav_sync_resumed", &[("files", files_processed.to_string())])
                                         };
                                         
                                         if let Err(e) = state_clone.db.create_notification(user_id, &notification).await {
@@ -161,15 +160,14 @@ impl WebDAVScheduler {
                                     
                                     // Send success notification if files were processed
                                     if files_processed > 0 {
+                                        let locale = crate::services::i18n::locale_of(&state_clone.db, user_id).await;
                                         let notification = crate::models::CreateNotification {
-                                            notification_type: "success".to_string(),
-                                            title: "WebDAV Sync Completed".to_string(),
-                                            message: format!("Successfully processed {} files from WebDAV sync", files_processed),
                                             action_url: Some("/documents".to_string()),
                                             metadata: Some(serde_json::json!({
                                                 "sync_type": "webdav",
                                                 "files_processed": files_processed
                                             })),
+                                            ..crate::services::i18n::notification(&locale, "success", "notification.webdav_sync_completed", &[("files", files_processed.to_string())])
                                         };
                                         
                                         if let Err(e) = state_clone.db.create_notification(user_id, &notification).await {
@@ -181,15 +179,14 @@ impl WebDAVScheduler {
                                     error!("Background WebDAV sync failed for user {}: {}", user_id, e);
                                     
                                     // Send error notification
+                                    let locale = crate::services::i18n::locale_of(&state_clone.db, user_id).await;
                                     let notification = crate::models::CreateNotification {
-                                        notification_type: "error".to_string(),
-                                        title: "WebDAV Sync Failed".to_string(),
-                                        message: format!("WebDAV sync encountered an error: {}", e),
                                         action_url: Some("/settings".to_string()),
                                         metadata: Some(serde_json::json!({
                                             "sync_type": "webdav",
                                             "error": e.to_string()
                                         })),
+                                        ..crate::services::i18n::notification(&locale, "error", "notification.webdav_sync_failed", &[("error", e.to_string())])
                                     };
                                     
                                     if let Err(e) = state_clone.db.create_notification(user_id, &notification).await {
@@ -0,0 +1,204 @@
+//! Knowledge-graph extraction over many documents at once. A batch runs in the
+//! background on a node running the analysis role, with a few LLM requests in flight,
+//! saves its progress after every chunk, continues on another node or after a restart
+//! and stops between chunks once it is cancelled or the server shuts down.
+
+use anyhow::{anyhow, Result};
+use futures::stream::{self, StreamExt};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::errors::panic::spawn_guarded;
+use crate::models::{
+    AnalysisBatch, AnalysisBatchStatus, BulkOperationError, EventType, QueueKind, LLM_FEATURE_GRAPH_EXTRACTION,
+    MAX_ANALYSIS_CONCURRENCY,
+};
+use crate::scheduling::shutdown;
+use crate::services::event_service::EventService;
+use crate::services::llm::llm_service::LLMService;
+use crate::services::{maintenance_service, processing_usage};
+
+/// Documents processed between progress updates and cancellation checks
+const PROGRESS_CHUNK_SIZE: usize = 20;
+/// Failures kept with a batch; later ones are only counted
+const MAX_RECORDED_ERRORS: usize = 100;
+/// How often a node looks for batches no node is working on
+const CLAIM_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// LLM requests all batches together may have in flight, so several batches do not
+/// multiply the load on the model
+static LLM_PERMITS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(MAX_ANALYSIS_CONCURRENCY as usize));
+
+/// How a run of a batch ended
+enum RunOutcome {
+    Completed,
+    Cancelled,
+    /// Stopped between chunks for a shutdown or because the analysis queue was paused,
+    /// to be continued by the next node claiming it
+    Interrupted,
+}
+
+#[derive(Clone)]
+pub struct AnalysisBatchService {
+    db: Database,
+}
+
+impl AnalysisBatchService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Run the batch in the background
+    pub fn spawn(&self, batch_id: Uuid) {
+        let service = self.clone();
+        let in_flight = shutdown::track();
+        spawn_guarded(format!("analysis batch {}", batch_id), async move {
+            let _in_flight = in_flight;
+            if let Err(e) = service.run(batch_id).await {
+                warn!("Analysis batch {} stopped: {}", batch_id, e);
+            }
+        });
+    }
+
+    /// Run the batches no node online is working on: those created on nodes without
+    /// the analysis role, and those of nodes that stopped, this one's last run included
+    pub async fn run_worker(self, node_id: Uuid) {
+        let mut interval = tokio::time::interval(CLAIM_INTERVAL);
+        loop {
+            interval.tick().await;
+            if shutdown::requested() {
+                return;
+            }
+            if maintenance_service::holds(QueueKind::Analysis) {
+                continue;
+            }
+            match self.db.claim_analysis_batches(node_id).await {
+                Ok(batch_ids) => {
+                    for batch_id in batch_ids {
+                        info!("Running analysis batch {} on this node", batch_id);
+                        self.spawn(batch_id);
+                    }
+                }
+                Err(e) => warn!("Failed to claim analysis batches: {}", e),
+            }
+        }
+    }
+
+    /// Analyze the batch's remaining documents
+    pub async fn run(&self, batch_id: Uuid) -> Result<()> {
+        let batch = self
+            .db
+            .get_analysis_batch(batch_id)
+            .await?
+            .ok_or_else(|| anyhow!("Analysis batch {} not found", batch_id))?;
+        if batch.status.is_finished() {
+            return Ok(());
+        }
+
+        self.db.start_analysis_batch(batch.id).await?;
+        let (status, error) = match self.process(&batch).await {
+            Ok(RunOutcome::Completed) => (AnalysisBatchStatus::Completed, None),
+            Ok(RunOutcome::Cancelled) => {
+                info!("Analysis batch {} was cancelled", batch.id);
+                return Ok(());
+            }
+            Ok(RunOutcome::Interrupted) => {
+                info!("Analysis batch {} stopped for shutdown or a pause, its progress is kept", batch.id);
+                self.db.release_analysis_batch(batch.id).await?;
+                return Ok(());
+            }
+            Err(e) => (AnalysisBatchStatus::Failed, Some(e.to_string())),
+        };
+
+        match &error {
+            None => info!("Analysis batch {} completed", batch.id),
+            Some(e) => warn!("Analysis batch {} failed: {}", batch.id, e),
+        }
+        self.db.finish_analysis_batch(batch.id, status, error.as_deref()).await?;
+        Ok(())
+    }
+
+    /// Analyze the remaining documents chunk by chunk, unless cancelled or stopped before
+    /// the last chunk
+    async fn process(&self, batch: &AnalysisBatch) -> Result<RunOutcome> {
+        let llm = LLMService::new(self.db.get_pool().clone());
+        let concurrency = batch.concurrency.clamp(1, MAX_ANALYSIS_CONCURRENCY) as usize;
+
+        let document_ids = self.db.get_analysis_batch_document_ids(batch.id).await?;
+        let mut recorded_errors = batch.errors.len();
+        let remaining = document_ids.get(batch.processed_documents.max(0) as usize..).unwrap_or_default();
+        for chunk in remaining.chunks(PROGRESS_CHUNK_SIZE) {
+            if shutdown::requested() || maintenance_service::holds(QueueKind::Analysis) {
+                return Ok(RunOutcome::Interrupted);
+            }
+            if self.db.get_analysis_batch_status(batch.id).await? != Some(AnalysisBatchStatus::Running) {
+                return Ok(RunOutcome::Cancelled);
+            }
+
+            let results: Vec<(Uuid, Result<(), String>)> = stream::iter(chunk.iter().copied())
+                .map(|document_id| {
+                    let llm = &llm;
+                    async move { (document_id, self.analyze(llm, batch, document_id).await) }
+                })
+                .buffer_unordered(concurrency)
+                .collect()
+                .await;
+
+            let mut succeeded = 0;
+            let mut failed = 0;
+            let mut errors = Vec::new();
+            for (document_id, result) in results {
+                match result {
+                    Ok(()) => succeeded += 1,
+                    Err(error) => {
+                        failed += 1;
+                        if recorded_errors < MAX_RECORDED_ERRORS {
+                            recorded_errors += 1;
+                            errors.push(BulkOperationError { document_id, error });
+                        }
+                    }
+                }
+            }
+            self.db
+                .update_analysis_batch_progress(batch.id, succeeded, failed, &errors)
+                .await?;
+        }
+
+        Ok(RunOutcome::Completed)
+    }
+
+    async fn analyze(&self, llm: &LLMService, batch: &AnalysisBatch, document_id: Uuid) -> Result<(), String> {
+        let _permit = LLM_PERMITS.acquire().await.map_err(|e| e.to_string())?;
+        let graph = processing_usage::for_document(document_id, llm.analyze_document(document_id)).await?;
+
+        // Graphs answered from the cache cost nothing
+        if llm.chat_enabled() && !graph.cached {
+            if let Err(e) = self
+                .db
+                .record_llm_usage(batch.user_id, LLM_FEATURE_GRAPH_EXTRACTION, 1, Some(document_id))
+                .await
+            {
+                warn!("Failed to record LLM usage of document {}: {}", document_id, e);
+            }
+        }
+
+        EventService::new(self.db.clone())
+            .publish_best_effort(
+                EventType::AnalysisCompleted,
+                Some(batch.user_id),
+                Some(document_id),
+                json!({
+                    "document_id": document_id,
+                    "node_count": graph.nodes.len(),
+                    "edge_count": graph.edges.len(),
+                    "batch_id": batch.id,
+                }),
+            )
+            .await;
+        Ok(())
+    }
+}
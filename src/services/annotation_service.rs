@@ -0,0 +1,79 @@
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{AnnotationChange, AnnotationResponse, Document, DocumentAnnotation, User, UserRole};
+
+/// Changes a slow viewer may fall behind by before it misses some
+const CHANGE_BUS_CAPACITY: usize = 256;
+
+/// A change to the annotations of a document
+#[derive(Debug, Clone)]
+pub struct DocumentAnnotationChange {
+    pub document_id: Uuid,
+    pub change: AnnotationChange,
+}
+
+/// Every annotation change made in this process, for the annotation WebSockets
+static CHANGE_BUS: Lazy<broadcast::Sender<DocumentAnnotationChange>> =
+    Lazy::new(|| broadcast::channel(CHANGE_BUS_CAPACITY).0);
+
+pub fn subscribe() -> broadcast::Receiver<DocumentAnnotationChange> {
+    CHANGE_BUS.subscribe()
+}
+
+/// Tells the viewers of the document about the change; nobody listening is fine
+pub fn publish(document_id: Uuid, change: AnnotationChange) {
+    let _ = CHANGE_BUS.send(DocumentAnnotationChange { document_id, change });
+}
+
+/// Whether the user may edit or delete something written by `author_id`: their own
+/// annotations and comments, and anything on their documents
+pub fn may_modify(user: &User, document: &Document, author_id: Option<Uuid>) -> bool {
+    user.role == UserRole::Admin || author_id == Some(user.id) || document.user_id == user.id
+}
+
+/// The annotations with their comment threads
+pub async fn with_comments(db: &Database, annotations: Vec<DocumentAnnotation>) -> Result<Vec<AnnotationResponse>> {
+    let ids: Vec<Uuid> = annotations.iter().map(|a| a.id).collect();
+    let mut comments_by_annotation: HashMap<Uuid, Vec<_>> = HashMap::new();
+    for comment in db.get_annotation_comments(&ids).await? {
+        comments_by_annotation.entry(comment.annotation_id).or_default().push(comment);
+    }
+
+    Ok(annotations
+        .into_iter()
+        .map(|annotation| AnnotationResponse {
+            comments: comments_by_annotation.remove(&annotation.id).unwrap_or_default(),
+            annotation,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_published_changes_reach_subscribers() {
+        let mut changes = subscribe();
+        let document_id = Uuid::new_v4();
+        let annotation_id = Uuid::new_v4();
+        publish(document_id, AnnotationChange::AnnotationDeleted { annotation_id });
+
+        // Other tests may publish on the same bus
+        loop {
+            let received = changes.recv().await.unwrap();
+            if received.document_id == document_id {
+                assert!(matches!(
+                    received.change,
+                    AnnotationChange::AnnotationDeleted { annotation_id: id } if id == annotation_id
+                ));
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Records significant actions in the audit log: uploads, views, downloads, deletions,
+//! label and settings changes and logins, with the user, client address and the
+//! values that changed.
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, HeaderMap},
+};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::auth::impersonator_from_headers;
+use crate::db::{audit_log::NewAuditLogEntry, Database};
+use crate::models::{Document, Impersonator, User};
+use crate::AppState;
+
+pub const DOCUMENT_UPLOAD: &str = "document.upload";
+pub const DOCUMENT_VIEW: &str = "document.view";
+pub const DOCUMENT_DOWNLOAD: &str = "document.download";
+pub const DOCUMENT_DELETE: &str = "document.delete";
+pub const DOCUMENT_LABELS_CHANGE: &str = "document.labels_change";
+pub const DOCUMENT_REDACT: &str = "document.redact";
+pub const DOCUMENT_SANITIZE: &str = "document.sanitize";
+pub const DOCUMENT_PAGES_ROTATE: &str = "document.pages_rotate";
+pub const DOCUMENT_OCR_TEXT_CORRECT: &str = "document.ocr_text_correct";
+pub const DOCUMENT_WORKFLOW_TRANSITION: &str = "document.workflow_transition";
+pub const FAILED_DOCUMENT_REQUEUE: &str = "document.failed_requeue";
+pub const LABEL_CREATE: &str = "label.create";
+pub const LABEL_UPDATE: &str = "label.update";
+pub const LABEL_DELETE: &str = "label.delete";
+pub const LABEL_MERGE: &str = "label.merge";
+pub const SHARE_LINK_CREATE: &str = "share_link.create";
+pub const SHARE_LINK_REVOKE: &str = "share_link.revoke";
+pub const SETTINGS_UPDATE: &str = "settings.update";
+pub const LOGIN: &str = "auth.login";
+pub const LOGIN_FAILED: &str = "auth.login_failed";
+pub const API_TOKEN_CREATE: &str = "api_token.create";
+pub const API_TOKEN_REVOKE: &str = "api_token.revoke";
+pub const CALENDAR_FEED_CREATE: &str = "calendar_feed.create";
+pub const CALENDAR_FEED_REVOKE: &str = "calendar_feed.revoke";
+pub const USER_ROLE_CHANGE: &str = "user.role_change";
+pub const TWO_FACTOR_ENABLE: &str = "auth.2fa_enable";
+pub const TWO_FACTOR_DISABLE: &str = "auth.2fa_disable";
+pub const AUTH_POLICY_UPDATE: &str = "auth.policy_update";
+pub const PASSKEY_ADD: &str = "auth.passkey_add";
+pub const PASSKEY_REMOVE: &str = "auth.passkey_remove";
+pub const LOGOUT: &str = "auth.logout";
+pub const SESSION_REVOKE: &str = "auth.session_revoke";
+pub const LIBRARY_EXPORT: &str = "library.export";
+pub const LIBRARY_IMPORT: &str = "library.import";
+pub const LIBRARY_BACKUP: &str = "library.backup";
+pub const QUARANTINE_DOWNLOAD: &str = "quarantine.download";
+pub const QUARANTINE_DELETE: &str = "quarantine.delete";
+pub const CONVERTER_QUARANTINE_RELEASE: &str = "quarantine.converter_release";
+pub const LEGAL_HOLD_CREATE: &str = "legal_hold.create";
+pub const LEGAL_HOLD_UPDATE: &str = "legal_hold.update";
+pub const LEGAL_HOLD_RELEASE: &str = "legal_hold.release";
+pub const LEGAL_HOLD_MANIFEST_EXPORT: &str = "legal_hold.manifest_export";
+pub const CONSISTENCY_REPAIR: &str = "consistency.repair";
+pub const INGESTION_HOOK_CREATE: &str = "ingestion_hook.create";
+pub const INGESTION_HOOK_UPDATE: &str = "ingestion_hook.update";
+pub const INGESTION_HOOK_DELETE: &str = "ingestion_hook.delete";
+pub const STORAGE_LAYOUT_UPDATE: &str = "storage.layout_update";
+pub const SCAN_DEVICE_CREATE: &str = "scan_device.create";
+pub const SCAN_DEVICE_UPDATE: &str = "scan_device.update";
+pub const SCAN_DEVICE_PASSWORD_RESET: &str = "scan_device.password_reset";
+pub const SCAN_DEVICE_DELETE: &str = "scan_device.delete";
+pub const SCAN_BRIDGE_PAIR: &str = "scan_bridge.pair";
+pub const SCAN_BRIDGE_UPDATE: &str = "scan_bridge.update";
+pub const SCAN_BRIDGE_DELETE: &str = "scan_bridge.delete";
+pub const GUEST_PORTAL_CREATE: &str = "guest_portal.create";
+pub const GUEST_PORTAL_UPDATE: &str = "guest_portal.update";
+pub const GUEST_PORTAL_DELETE: &str = "guest_portal.delete";
+pub const INSTANCE_SETTINGS_UPDATE: &str = "instance.settings_update";
+pub const MAINTENANCE_MODE_UPDATE: &str = "instance.maintenance_update";
+pub const QUEUE_PAUSE: &str = "queue.pause";
+pub const QUEUE_RESUME: &str = "queue.resume";
+pub const EMBARGO_CREATE: &str = "embargo.create";
+pub const EMBARGO_RELEASE: &str = "embargo.release";
+pub const SOURCE_EMBARGO_UPDATE: &str = "embargo.source_update";
+pub const CONTENT_TRANSMIT: &str = "content.transmit";
+pub const CONTENT_TRANSMIT_BLOCKED: &str = "content.transmit_blocked";
+pub const USER_IMPERSONATE: &str = "user.impersonate";
+
+/// Values under keys containing these are replaced before they are written
+const REDACTED_KEYS: [&str; 4] = ["password", "secret", "token", "api_key"];
+const REDACTED: &str = "[redacted]";
+
+/// Whether to take the client address from `X-Forwarded-For` / `X-Real-IP`, which
+/// only a trusted reverse proxy should set
+static TRUST_PROXY_HEADERS: Lazy<bool> = Lazy::new(|| {
+    std::env::var("AUDIT_TRUST_PROXY_HEADERS")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+});
+
+/// Address and user agent of the client making a request
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// The admin acting as the user, when the request is made with an impersonation token
+    pub impersonator: Option<Impersonator>,
+}
+
+impl FromRequestParts<Arc<AppState>> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let peer = parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|info| info.0);
+        Ok(ClientInfo {
+            ip_address: client_ip(&parts.headers, peer, *TRUST_PROXY_HEADERS),
+            user_agent: parts
+                .headers
+                .get(header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.chars().take(512).collect()),
+            impersonator: impersonator_from_headers(&parts.headers, &state.config.jwt_secret),
+        })
+    }
+}
+
+fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, trust_proxy_headers: bool) -> Option<String> {
+    if trust_proxy_headers {
+        let header_value = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        // The first address is the client; later ones are proxies it passed through
+        let forwarded = header_value("x-forwarded-for")
+            .and_then(|v| v.split(',').next())
+            .or_else(|| header_value("x-real-ip"))
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        if let Some(ip) = forwarded {
+            return Some(ip.to_string());
+        }
+    }
+    peer.map(|addr| addr.ip().to_string())
+}
+
+/// An action to record; build with [`AuditEvent::new`] and the setters
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    action: &'static str,
+    resource_type: &'static str,
+    resource_id: Option<Uuid>,
+    before: Option<Value>,
+    after: Option<Value>,
+    details: Option<Value>,
+}
+
+impl AuditEvent {
+    pub fn new(action: &'static str, resource_type: &'static str, resource_id: Option<Uuid>) -> Self {
+        Self { action, resource_type, resource_id, before: None, after: None, details: None }
+    }
+
+    pub fn before(mut self, value: Value) -> Self {
+        self.before = Some(redact(value));
+        self
+    }
+
+    pub fn after(mut self, value: Value) -> Self {
+        self.after = Some(redact(value));
+        self
+    }
+
+    /// Keep only the fields that differ between two versions of an object
+    pub fn changes(self, before: &Value, after: &Value) -> Self {
+        let (before, after) = changed_fields(before, after);
+        self.before(before).after(after)
+    }
+
+    pub fn details(mut self, value: Value) -> Self {
+        self.details = Some(redact(value));
+        self
+    }
+}
+
+/// Write an event to the audit log. `actor` is None for actions the system takes on
+/// its own. Failures are logged rather than returned, so auditing never breaks the
+/// action itself. Views and downloads of documents also count towards their access
+/// statistics.
+pub async fn record(db: &Database, actor: Option<&User>, client: &ClientInfo, event: AuditEvent) {
+    if let (Some((views, downloads)), Some(document_id)) = (access_counts(&event), event.resource_id) {
+        if let Err(e) = db.record_document_access(document_id, actor.map(|user| user.id), views, downloads).await {
+            warn!("Failed to count access to document {}: {}", document_id, e);
+        }
+    }
+
+    let entry = NewAuditLogEntry {
+        user_id: actor.map(|user| user.id),
+        username: actor.map(|user| user.username.clone()),
+        action: event.action.to_string(),
+        resource_type: event.resource_type.to_string(),
+        resource_id: event.resource_id,
+        ip_address: client.ip_address.clone(),
+        user_agent: client.user_agent.clone(),
+        impersonator_id: client.impersonator.as_ref().map(|impersonator| impersonator.id),
+        impersonator_username: client.impersonator.as_ref().map(|impersonator| impersonator.username.clone()),
+        before: event.before,
+        after: event.after,
+        details: event.details,
+    };
+
+    if let Err(e) = db.create_audit_log_entry(&entry).await {
+        warn!("Failed to write {} audit log entry: {}", entry.action, e);
+    }
+}
+
+/// Reads of the same document by the same user within this time are one access:
+/// a resumed or seeking download fetches the file in several ranges
+const FILE_ACCESS_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Accesses remembered at most; older ones are dropped first
+const MAX_RECENT_FILE_ACCESSES: usize = 10_000;
+
+type FileAccessKey = (Option<Uuid>, Uuid, &'static str);
+
+static RECENT_FILE_ACCESSES: Lazy<Mutex<HashMap<FileAccessKey, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Record a view or download of a document's file, for every full or partial response,
+/// unless the same user read the same document the same way within `FILE_ACCESS_WINDOW`
+pub async fn record_file_access(db: &Database, actor: Option<&User>, client: &ClientInfo, event: AuditEvent) {
+    if let Some(document_id) = event.resource_id {
+        let key = (actor.map(|user| user.id), document_id, event.action);
+        let mut recent = RECENT_FILE_ACCESSES.lock().unwrap_or_else(|e| e.into_inner());
+        if !first_access(&mut recent, key, Instant::now()) {
+            return;
+        }
+    }
+    record(db, actor, client, event).await;
+}
+
+/// Whether `key` was not accessed within `FILE_ACCESS_WINDOW` before `now`, remembering
+/// this access either way
+fn first_access(recent: &mut HashMap<FileAccessKey, Instant>, key: FileAccessKey, now: Instant) -> bool {
+    if recent.len() >= MAX_RECENT_FILE_ACCESSES {
+        recent.retain(|_, at| now.saturating_duration_since(*at) < FILE_ACCESS_WINDOW);
+    }
+    if recent.len() >= MAX_RECENT_FILE_ACCESSES {
+        if let Some(oldest) = recent.iter().min_by_key(|(_, at)| **at).map(|(key, _)| *key) {
+            recent.remove(&oldest);
+        }
+    }
+    let first = recent
+        .get(&key)
+        .is_none_or(|at| now.saturating_duration_since(*at) >= FILE_ACCESS_WINDOW);
+    recent.insert(key, now);
+    first
+}
+
+/// Views and downloads an event adds to a document's access statistics
+fn access_counts(event: &AuditEvent) -> Option<(i64, i64)> {
+    if event.resource_type != "document" {
+        return None;
+    }
+    match event.action {
+        DOCUMENT_VIEW => Some((1, 0)),
+        DOCUMENT_DOWNLOAD => Some((0, 1)),
+        _ => None,
+    }
+}
+
+/// What identifies a document in audit entries, so they stay meaningful after it is deleted
+pub fn document_details(document: &Document) -> Value {
+    serde_json::json!({
+        "filename": document.original_filename,
+        "mime_type": document.mime_type,
+        "file_size": document.file_size,
+        "file_hash": document.file_hash,
+    })
+}
+
+/// The fields of two objects whose values differ, as (before, after). Values that
+/// are not both objects are returned whole.
+fn changed_fields(before: &Value, after: &Value) -> (Value, Value) {
+    let (Value::Object(old), Value::Object(new)) = (before, after) else {
+        return (before.clone(), after.clone());
+    };
+
+    let mut old_changed = Map::new();
+    let mut new_changed = Map::new();
+    for key in old.keys().chain(new.keys().filter(|key| !old.contains_key(*key))) {
+        let old_value = old.get(key).unwrap_or(&Value::Null);
+        let new_value = new.get(key).unwrap_or(&Value::Null);
+        if old_value != new_value {
+            old_changed.insert(key.clone(), old_value.clone());
+            new_changed.insert(key.clone(), new_value.clone());
+        }
+    }
+    (Value::Object(old_changed), Value::Object(new_changed))
+}
+
+/// Replace the values of secret-looking keys, keeping whether they were set
+fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let lower = key.to_ascii_lowercase();
+                    if REDACTED_KEYS.iter().any(|secret| lower.contains(secret)) && !value.is_null() {
+                        (key, Value::String(REDACTED.to_string()))
+                    } else {
+                        (key, redact(value))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    #[test]
+    fn test_changed_fields_are_redacted() {
+        let before = json!({ "ocr_language": "eng", "retention_days": null, "webdav_password": "old" });
+        let after = json!({ "ocr_language": "deu", "retention_days": null, "webdav_password": "new" });
+
+        let event = AuditEvent::new(SETTINGS_UPDATE, "settings", None).changes(&before, &after);
+        assert_eq!(event.before, Some(json!({ "ocr_language": "eng", "webdav_password": REDACTED })));
+        assert_eq!(event.after, Some(json!({ "ocr_language": "deu", "webdav_password": REDACTED })));
+    }
+
+    #[test]
+    fn test_access_counts() {
+        let id = Some(Uuid::new_v4());
+        assert_eq!(access_counts(&AuditEvent::new(DOCUMENT_VIEW, "document", id)), Some((1, 0)));
+        assert_eq!(access_counts(&AuditEvent::new(DOCUMENT_DOWNLOAD, "document", id)), Some((0, 1)));
+        assert_eq!(access_counts(&AuditEvent::new(DOCUMENT_DELETE, "document", id)), None);
+        assert_eq!(access_counts(&AuditEvent::new(DOCUMENT_DOWNLOAD, "quarantine", id)), None);
+    }
+
+    #[test]
+    fn test_file_accesses_within_window_are_one_access() {
+        let mut recent = HashMap::new();
+        let (user, document) = (Some(Uuid::new_v4()), Uuid::new_v4());
+        let start = Instant::now();
+
+        assert!(first_access(&mut recent, (user, document, DOCUMENT_DOWNLOAD), start));
+        // Later ranges of the same download
+        assert!(!first_access(&mut recent, (user, document, DOCUMENT_DOWNLOAD), start + Duration::from_secs(5)));
+        // Another user, another document or a view are accesses of their own
+        assert!(first_access(&mut recent, (Some(Uuid::new_v4()), document, DOCUMENT_DOWNLOAD), start));
+        assert!(first_access(&mut recent, (user, Uuid::new_v4(), DOCUMENT_DOWNLOAD), start));
+        assert!(first_access(&mut recent, (user, document, DOCUMENT_VIEW), start));
+        // Reading again after the window counts again
+        let later = start + Duration::from_secs(5) + FILE_ACCESS_WINDOW;
+        assert!(first_access(&mut recent, (user, document, DOCUMENT_DOWNLOAD), later));
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let peer: Option<SocketAddr> = Some("10.0.0.2:51234".parse().unwrap());
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("203.0.113.7, 10.0.0.1"));
+
+        assert_eq!(client_ip(&headers, peer, true).as_deref(), Some("203.0.113.7"));
+        assert_eq!(client_ip(&headers, peer, false).as_deref(), Some("10.0.0.2"));
+        assert_eq!(client_ip(&HeaderMap::new(), peer, true).as_deref(), Some("10.0.0.2"));
+        assert_eq!(client_ip(&HeaderMap::new(), None, true), None);
+    }
+}
@@ -0,0 +1,489 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::io::ReaderStream;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        Backup, BackupKind, BackupOverview, BackupStatus, ExportStatus, S3SourceConfig, UserRole,
+    },
+    services::export_service::ExportService,
+    services::i18n,
+    services::s3_service::S3Service,
+    AppState,
+};
+
+const DEFAULT_INTERVAL_HOURS: u64 = 24;
+const DEFAULT_RETENTION_COUNT: usize = 7;
+const DEFAULT_FULL_EVERY: u32 = 7;
+const DEFAULT_S3_PREFIX: &str = "readur-backups";
+/// How often the scheduler checks whether a backup is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Wait after a failed backup before trying again, unless the interval is shorter
+const RETRY_DELAY_HOURS: i64 = 1;
+const WEBDAV_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+static BACKUP_CONFIG: Lazy<Result<Option<BackupConfig>, String>> =
+    Lazy::new(|| BackupConfig::from_env().map_err(|e| e.to_string()));
+
+/// The backup configuration from the environment: None when backups are off, an error
+/// when `BACKUP_DESTINATION` is set but unusable
+pub fn configured() -> Result<Option<BackupConfig>, String> {
+    BACKUP_CONFIG.clone()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupMode {
+    /// Every backup holds the whole library
+    Full,
+    /// Backups between full ones hold the documents changed since the previous backup
+    Incremental,
+}
+
+impl BackupMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupMode::Full => "full",
+            BackupMode::Incremental => "incremental",
+        }
+    }
+}
+
+/// Where backup archives are uploaded
+#[derive(Debug, Clone)]
+pub enum BackupTarget {
+    S3 {
+        config: S3SourceConfig,
+        /// Key prefix the archives are uploaded under
+        prefix: String,
+    },
+    WebDav {
+        /// Collection the archives are uploaded to; it must exist
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+    },
+}
+
+impl BackupTarget {
+    pub fn destination(&self) -> &'static str {
+        match self {
+            BackupTarget::S3 { .. } => "s3",
+            BackupTarget::WebDav { .. } => "webdav",
+        }
+    }
+
+    /// Where the archives go, without credentials
+    pub fn describe(&self) -> String {
+        match self {
+            BackupTarget::S3 { config, prefix } => format!("s3://{}/{}", config.bucket_name, prefix),
+            BackupTarget::WebDav { url, .. } => url.clone(),
+        }
+    }
+
+    /// Upload the archive as `name`. Returns the S3 key or WebDAV URL it was stored at.
+    async fn upload(&self, archive: &Path, name: &str) -> Result<String> {
+        match self {
+            BackupTarget::S3 { config, prefix } => {
+                let key = if prefix.is_empty() { name.to_string() } else { format!("{}/{}", prefix, name) };
+                S3Service::new(config.clone()).await?.store_file_from_path(&key, archive).await?;
+                Ok(key)
+            }
+            BackupTarget::WebDav { url, username, password } => {
+                let file_url = format!("{}/{}", url, name);
+                let file = tokio::fs::File::open(archive).await?;
+                let size = file.metadata().await?.len();
+                let mut request = webdav_client()?
+                    .put(&file_url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/zip")
+                    .header(reqwest::header::CONTENT_LENGTH, size)
+                    .body(reqwest::Body::wrap_stream(ReaderStream::new(file)));
+                if let Some(username) = username {
+                    request = request.basic_auth(username, password.as_deref());
+                }
+                let response = request.send().await?;
+                if !response.status().is_success() {
+                    return Err(anyhow!("Upload to {} failed: HTTP {}", file_url, response.status()));
+                }
+                Ok(file_url)
+            }
+        }
+    }
+
+    /// Remove an uploaded archive; one that is already gone is not an error
+    async fn remove(&self, remote_path: &str) -> Result<()> {
+        match self {
+            BackupTarget::S3 { config, .. } => S3Service::new(config.clone()).await?.delete_file(remote_path).await,
+            BackupTarget::WebDav { username, password, .. } => {
+                let mut request = webdav_client()?.delete(remote_path);
+                if let Some(username) = username {
+                    request = request.basic_auth(username, password.as_deref());
+                }
+                let response = request.send().await?;
+                if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
+                    return Err(anyhow!("Removing {} failed: HTTP {}", remote_path, response.status()));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn webdav_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder().connect_timeout(WEBDAV_CONNECT_TIMEOUT).build()?)
+}
+
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    pub target: BackupTarget,
+    pub mode: BackupMode,
+    pub interval_hours: u64,
+    /// Full backups kept at the destination, with the incremental backups after them
+    pub retention_count: usize,
+    /// In incremental mode, every how many backups a full one is made
+    pub full_every: u32,
+}
+
+impl BackupConfig {
+    /// None unless `BACKUP_DESTINATION` is set
+    pub fn from_env() -> Result<Option<Self>> {
+        let target = match env_value("BACKUP_DESTINATION") {
+            None => return Ok(None),
+            Some(destination) => match destination.to_lowercase().as_str() {
+                "s3" => Self::s3_target_from_env()?,
+                "webdav" => BackupTarget::WebDav {
+                    url: env_value("BACKUP_WEBDAV_URL")
+                        .ok_or_else(|| anyhow!("BACKUP_WEBDAV_URL is required when BACKUP_DESTINATION is webdav"))?
+                        .trim_end_matches('/')
+                        .to_string(),
+                    username: env_value("BACKUP_WEBDAV_USERNAME"),
+                    password: env_value("BACKUP_WEBDAV_PASSWORD"),
+                },
+                other => return Err(anyhow!("Unknown BACKUP_DESTINATION '{}' (expected s3 or webdav)", other)),
+            },
+        };
+        let mode = match env_value("BACKUP_MODE").map(|v| v.to_lowercase()).as_deref() {
+            None | Some("full") => BackupMode::Full,
+            Some("incremental") => BackupMode::Incremental,
+            Some(other) => return Err(anyhow!("Unknown BACKUP_MODE '{}' (expected full or incremental)", other)),
+        };
+
+        Ok(Some(Self {
+            target,
+            mode,
+            interval_hours: env_number("BACKUP_INTERVAL_HOURS").unwrap_or(DEFAULT_INTERVAL_HOURS).max(1),
+            retention_count: env_number("BACKUP_RETENTION_COUNT").unwrap_or(DEFAULT_RETENTION_COUNT).max(1),
+            full_every: env_number("BACKUP_FULL_EVERY").unwrap_or(DEFAULT_FULL_EVERY).max(1),
+        }))
+    }
+
+    /// `BACKUP_S3_*` settings, each falling back to the `S3_*` storage setting
+    fn s3_target_from_env() -> Result<BackupTarget> {
+        let s3_value = |name: &str| env_value(&format!("BACKUP_{}", name)).or_else(|| env_value(name));
+        let bucket_name = s3_value("S3_BUCKET_NAME")
+            .ok_or_else(|| anyhow!("BACKUP_S3_BUCKET_NAME or S3_BUCKET_NAME is required when BACKUP_DESTINATION is s3"))?;
+        let config = S3SourceConfig {
+            bucket_name,
+            region: s3_value("S3_REGION").unwrap_or_else(|| "us-east-1".to_string()),
+            access_key_id: s3_value("S3_ACCESS_KEY_ID").unwrap_or_default(),
+            secret_access_key: s3_value("S3_SECRET_ACCESS_KEY").unwrap_or_default(),
+            endpoint_url: s3_value("S3_ENDPOINT_URL"),
+            prefix: None,
+            watch_folders: vec![],
+            file_extensions: vec![],
+            auto_sync: false,
+            sync_interval_minutes: 0,
+            notifications: None,
+        };
+        let prefix = std::env::var("BACKUP_S3_PREFIX")
+            .unwrap_or_else(|_| DEFAULT_S3_PREFIX.to_string())
+            .trim_matches('/')
+            .to_string();
+
+        Ok(BackupTarget::S3 { config, prefix })
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn env_number<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env_value(name).and_then(|v| v.parse().ok())
+}
+
+/// Name of a backup's archive at the destination, which sorts by time
+pub fn backup_file_name(backup: &Backup) -> String {
+    format!("readur-backup-{}-{}.zip", backup.created_at.format("%Y%m%d-%H%M%S"), backup.kind)
+}
+
+/// Scheduled backups: writes a library export of every user's documents, uploads it to
+/// the configured S3 bucket or WebDAV collection and keeps the last
+/// `BACKUP_RETENTION_COUNT` full backups there. The local archive is deleted once it
+/// is uploaded. Admins are notified when a backup fails.
+pub struct BackupService {
+    state: Arc<AppState>,
+    config: BackupConfig,
+}
+
+impl BackupService {
+    pub fn new(state: Arc<AppState>, config: BackupConfig) -> Self {
+        Self { state, config }
+    }
+
+    /// Back up whenever the last successful backup is `BACKUP_INTERVAL_HOURS` old
+    pub async fn run(self) {
+        if let Err(e) = self.discard_interrupted().await {
+            error!("Failed to clean up interrupted backups: {}", e);
+        }
+
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.next_backup_at().await {
+                Ok(next) if next <= Utc::now() => match self.start(None).await {
+                    Ok(Some(backup)) => {
+                        if let Err(e) = self.run_backup(backup).await {
+                            error!("Failed to record backup: {}", e);
+                        }
+                    }
+                    // A backup started by an admin is still running
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to start backup: {}", e),
+                },
+                Ok(_) => {}
+                Err(e) => error!("Failed to check for a due backup: {}", e),
+            }
+        }
+    }
+
+    /// Fail the backups that were running when the server stopped and remove their exports
+    async fn discard_interrupted(&self) -> Result<()> {
+        let export_ids = self.state.db.fail_interrupted_backups().await?;
+        if !export_ids.is_empty() {
+            warn!("Marked {} interrupted backup(s) as failed", export_ids.len());
+        }
+        for export_id in export_ids {
+            self.discard_export(export_id).await;
+        }
+        Ok(())
+    }
+
+    /// When the next scheduled backup is due: an interval after the last successful
+    /// one, and not sooner than an hour after the last attempt
+    pub async fn next_backup_at(&self) -> Result<DateTime<Utc>> {
+        let interval = chrono::Duration::hours(self.config.interval_hours as i64);
+        let mut next = match self.state.db.get_latest_backup(Some(BackupStatus::Completed), None).await? {
+            Some(backup) => backup.created_at + interval,
+            None => Utc::now(),
+        };
+        if let Some(last) = self.state.db.get_latest_backup(None, None).await? {
+            next = next.max(last.created_at + interval.min(chrono::Duration::hours(RETRY_DELAY_HOURS)));
+        }
+        Ok(next)
+    }
+
+    /// Record a new backup, full or incremental as the mode and the last full backup
+    /// require. None when another backup is running.
+    pub async fn start(&self, triggered_by: Option<Uuid>) -> Result<Option<Backup>> {
+        let (kind, changed_since) = self.next_kind().await?;
+        self.state
+            .db
+            .create_backup(self.config.target.destination(), kind, changed_since, triggered_by)
+            .await
+    }
+
+    async fn next_kind(&self) -> Result<(BackupKind, Option<DateTime<Utc>>)> {
+        if self.config.mode == BackupMode::Full {
+            return Ok((BackupKind::Full, None));
+        }
+        let db = &self.state.db;
+        let Some(last_full) = db.get_latest_backup(Some(BackupStatus::Completed), Some(BackupKind::Full)).await? else {
+            return Ok((BackupKind::Full, None));
+        };
+        let since_full = db.count_completed_backups_since(last_full.created_at).await?;
+        if since_full + 1 >= i64::from(self.config.full_every) {
+            return Ok((BackupKind::Full, None));
+        }
+        let previous = db
+            .get_latest_backup(Some(BackupStatus::Completed), None)
+            .await?
+            .unwrap_or(last_full);
+        Ok((BackupKind::Incremental, Some(previous.created_at)))
+    }
+
+    /// Export, upload and prune. A failure is recorded with the backup; only failing to
+    /// record it is returned as an error.
+    pub async fn run_backup(&self, backup: Backup) -> Result<Backup> {
+        info!("Starting {} backup {} to {}", backup.kind, backup.id, self.config.target.describe());
+        match self.write_and_upload(&backup).await {
+            Ok(backup) => {
+                info!(
+                    "Backup {} uploaded to {} ({} documents)",
+                    backup.id,
+                    backup.remote_path.as_deref().unwrap_or_default(),
+                    backup.total_documents
+                );
+                if let Err(e) = self.prune().await {
+                    warn!("Failed to remove old backups: {}", e);
+                }
+                Ok(backup)
+            }
+            Err(e) => {
+                warn!("Backup {} failed: {}", backup.id, e);
+                self.notify_failure(&e.to_string()).await;
+                self.state.db.fail_backup(backup.id, &e.to_string()).await
+            }
+        }
+    }
+
+    async fn write_and_upload(&self, backup: &Backup) -> Result<Backup> {
+        let db = &self.state.db;
+        let owner_id = db
+            .get_backup_owner_id()
+            .await?
+            .ok_or_else(|| anyhow!("There is no admin to write the backup as"))?;
+        let document_ids = db.get_library_document_ids(None, backup.changed_since).await?;
+        let export = db.create_export(owner_id, true, &document_ids).await?;
+        db.set_backup_export(backup.id, export.id, export.total_documents).await?;
+
+        let uploaded = self.upload_export(export.id, backup).await;
+        // The archive is only kept at the destination
+        self.discard_export(export.id).await;
+        let (remote_path, archive_size, failed_documents) = uploaded?;
+
+        db.complete_backup(backup.id, &remote_path, archive_size, failed_documents).await
+    }
+
+    async fn upload_export(&self, export_id: Uuid, backup: &Backup) -> Result<(String, i64, i32)> {
+        let export_service = self.export_service();
+        let export = export_service.run(export_id).await?;
+        if export.status != ExportStatus::Completed {
+            return Err(anyhow!("Export failed: {}", export.error.unwrap_or_default()));
+        }
+
+        let archive_path = export_service.archive_path(export.id);
+        let remote_path = self.config.target.upload(&archive_path, &backup_file_name(backup)).await?;
+        Ok((remote_path, export.archive_size.unwrap_or_default(), export.failed_documents))
+    }
+
+    /// Remove the uploaded backups beyond the retention count
+    async fn prune(&self) -> Result<()> {
+        for backup in self.state.db.get_backups_to_prune(self.config.retention_count as i64).await? {
+            let Some(remote_path) = backup.remote_path.as_deref() else {
+                continue;
+            };
+            match self.config.target.remove(remote_path).await {
+                Ok(()) => {
+                    info!("Removed backup {} at {}", backup.id, remote_path);
+                    self.state.db.mark_backup_pruned(backup.id).await?;
+                }
+                Err(e) => warn!("Failed to remove backup {} at {}: {}", backup.id, remote_path, e),
+            }
+        }
+        Ok(())
+    }
+
+    fn export_service(&self) -> ExportService {
+        ExportService::new(self.state.db.clone(), self.state.file_service.as_ref().clone())
+    }
+
+    /// Delete an export written for a backup, with its finished or partial archive
+    async fn discard_export(&self, export_id: Uuid) {
+        let export_service = self.export_service();
+        let partial_path = export_service.archive_path(export_id).with_extension("zip.partial");
+        if let Err(e) = tokio::fs::remove_file(&partial_path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove partial archive {}: {}", partial_path.display(), e);
+            }
+        }
+        if let Err(e) = export_service.delete_archive(export_id).await {
+            warn!("Failed to delete archive of export {}: {}", export_id, e);
+        }
+        if let Err(e) = self.state.db.delete_export(export_id).await {
+            warn!("Failed to delete export {}: {}", export_id, e);
+        }
+    }
+
+    async fn notify_failure(&self, error: &str) {
+        let values = [("target", self.config.target.describe()), ("error", error.to_string())];
+        let users = match self.state.db.get_all_users().await {
+            Ok(users) => users,
+            Err(e) => {
+                warn!("Failed to notify admins of the failed backup: {}", e);
+                return;
+            }
+        };
+        for admin in users.iter().filter(|user| user.role == UserRole::Admin) {
+            let locale = i18n::locale_of(&self.state.db, admin.id).await;
+            let notification = i18n::notification(&locale, "error", "notification.backup_failed", &values);
+            if let Err(e) = self.state.db.create_notification(admin.id, &notification).await {
+                warn!("Failed to notify admin {} of the failed backup: {}", admin.id, e);
+            }
+        }
+    }
+
+    pub async fn overview(&self) -> Result<BackupOverview> {
+        let mut overview = overview_without_config(&self.state).await?;
+        overview.enabled = true;
+        overview.destination = Some(self.config.target.destination().to_string());
+        overview.target = Some(self.config.target.describe());
+        overview.mode = Some(self.config.mode.as_str().to_string());
+        overview.interval_hours = Some(self.config.interval_hours);
+        overview.retention_count = Some(self.config.retention_count);
+        overview.next_backup_at = Some(self.next_backup_at().await?);
+        Ok(overview)
+    }
+}
+
+/// How past backups went, for when backups are not configured
+pub async fn overview_without_config(state: &AppState) -> Result<BackupOverview> {
+    Ok(BackupOverview {
+        enabled: false,
+        configuration_error: None,
+        destination: None,
+        target: None,
+        mode: None,
+        interval_hours: None,
+        retention_count: None,
+        last_backup: state.db.get_latest_backup(None, None).await?,
+        last_successful_at: state
+            .db
+            .get_latest_backup(Some(BackupStatus::Completed), None)
+            .await?
+            .map(|backup| backup.created_at),
+        next_backup_at: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_file_name_sorts_by_time() {
+        let backup = Backup {
+            id: Uuid::new_v4(),
+            export_id: None,
+            destination: "s3".to_string(),
+            kind: BackupKind::Incremental,
+            changed_since: None,
+            status: BackupStatus::Running,
+            triggered_by: None,
+            remote_path: None,
+            archive_size: None,
+            total_documents: 0,
+            failed_documents: 0,
+            error: None,
+            created_at: "2026-03-04T05:06:07Z".parse().unwrap(),
+            completed_at: None,
+            pruned_at: None,
+        };
+        assert_eq!(backup_file_name(&backup), "readur-backup-20260304-050607-incremental.zip");
+    }
+}
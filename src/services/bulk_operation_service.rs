@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    models::{BulkOperation, BulkOperationError, BulkOperationKind, BulkOperationStatus, User, UserRole},
+    routes::documents::{crud::remove_document_as, pages::merge_documents_as},
+    routes::documents_ocr_retry::reset_document_ocr_status,
+    services::audit_service::{self, document_details, AuditEvent, ClientInfo},
+    services::event_service::EventService,
+    services::legal_hold_service,
+    services::title_service::TitleService,
+    AppState,
+};
+
+/// Documents processed between progress updates
+const PROGRESS_BATCH_SIZE: usize = 25;
+/// Failures kept with an operation; later ones are only counted
+const MAX_RECORDED_ERRORS: usize = 100;
+
+/// Runs bulk operations in the background one document at a time. Progress is saved
+/// after every batch, so clients can follow it and an operation interrupted by a
+/// restart continues where it stopped. A failing document is recorded and skipped.
+pub struct BulkOperationService {
+    state: Arc<AppState>,
+}
+
+impl BulkOperationService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Continue the operations that were queued or running when the server stopped
+    pub async fn resume_interrupted(&self) -> Result<()> {
+        for operation in self.state.db.get_unfinished_bulk_operations().await? {
+            info!("Resuming bulk operation {} ({})", operation.id, operation.operation);
+            if let Err(e) = self.run(operation.id, ClientInfo::default()).await {
+                warn!("Bulk operation {} stopped: {}", operation.id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Process the operation's remaining documents. `client` is recorded in the audit
+    /// log as the origin of deletions and label changes.
+    pub async fn run(&self, operation_id: Uuid, client: ClientInfo) -> Result<BulkOperation> {
+        let operation = self
+            .state
+            .db
+            .get_bulk_operation(operation_id)
+            .await?
+            .ok_or_else(|| anyhow!("Bulk operation {} not found", operation_id))?;
+        if matches!(operation.status, BulkOperationStatus::Completed | BulkOperationStatus::Failed) {
+            return Ok(operation);
+        }
+
+        self.state.db.start_bulk_operation(operation.id).await?;
+        match self.process(&operation, &client).await {
+            Ok(merged_document_id) => {
+                info!("Bulk operation {} ({}) completed", operation.id, operation.operation);
+                self.state
+                    .db
+                    .finish_bulk_operation(operation.id, BulkOperationStatus::Completed, merged_document_id, None)
+                    .await
+            }
+            Err(e) => {
+                warn!("Bulk operation {} ({}) failed: {}", operation.id, operation.operation, e);
+                self.state
+                    .db
+                    .finish_bulk_operation(operation.id, BulkOperationStatus::Failed, None, Some(&e.to_string()))
+                    .await
+            }
+        }
+    }
+
+    /// Returns the merged document of a merge
+    async fn process(&self, operation: &BulkOperation, client: &ClientInfo) -> Result<Option<Uuid>> {
+        let user = self
+            .state
+            .db
+            .get_user_by_id(operation.user_id)
+            .await?
+            .ok_or_else(|| anyhow!("User {} no longer exists", operation.user_id))?;
+        if operation.operation == BulkOperationKind::ChangeOwner && user.role != UserRole::Admin {
+            return Err(anyhow!("Only admins can change the owner of documents"));
+        }
+
+        let document_ids = self.state.db.get_bulk_operation_document_ids(operation.id).await?;
+        if operation.operation == BulkOperationKind::Merge {
+            return self.merge(operation, &user, &document_ids).await.map(Some);
+        }
+
+        let mut recorded_errors = operation.errors.len();
+        let remaining = document_ids.get(operation.processed_documents.max(0) as usize..).unwrap_or_default();
+        for batch in remaining.chunks(PROGRESS_BATCH_SIZE) {
+            let mut succeeded = 0;
+            let mut failed = 0;
+            let mut errors = Vec::new();
+
+            for &document_id in batch {
+                match self.apply(operation, &user, client, document_id).await {
+                    Ok(()) => succeeded += 1,
+                    Err(e) => {
+                        failed += 1;
+                        if recorded_errors < MAX_RECORDED_ERRORS {
+                            recorded_errors += 1;
+                            errors.push(BulkOperationError { document_id, error: e.to_string() });
+                        }
+                    }
+                }
+            }
+
+            self.state
+                .db
+                .update_bulk_operation_progress(operation.id, succeeded, failed, &errors)
+                .await?;
+        }
+
+        Ok(None)
+    }
+
+    async fn apply(&self, operation: &BulkOperation, user: &User, client: &ClientInfo, document_id: Uuid) -> Result<()> {
+        let document = self
+            .state
+            .db
+            .get_document_by_id(document_id, user.id, user.role)
+            .await?
+            .ok_or_else(|| anyhow!("Document not found"))?;
+        let parameters = &operation.parameters;
+
+        match operation.operation {
+            BulkOperationKind::AddLabels => {
+                for label_id in &parameters.label_ids {
+                    self.state.db.assign_label(document.id, *label_id, user.id).await?;
+                }
+                self.record_labels_change(operation, user, client, document.id, "added").await;
+            }
+            BulkOperationKind::RemoveLabels => {
+                self.state.db.remove_document_labels(document.id, &parameters.label_ids).await?;
+                self.record_labels_change(operation, user, client, document.id, "removed").await;
+            }
+            BulkOperationKind::Delete => {
+                legal_hold_service::ensure_not_held(&self.state.db, document.id).await?;
+                if !remove_document_as(&self.state, &document, user.id, user.role).await? {
+                    return Err(anyhow!("Document not found"));
+                }
+                audit_service::record(
+                    &self.state.db,
+                    Some(user),
+                    client,
+                    AuditEvent::new(audit_service::DOCUMENT_DELETE, "document", Some(document.id)).details(
+                        serde_json::json!({
+                            "bulk_operation_id": operation.id,
+                            "document": document_details(&document),
+                        }),
+                    ),
+                )
+                .await;
+            }
+            BulkOperationKind::ReprocessOcr => {
+                if document.ocr_status.as_deref() == Some("processing") {
+                    return Err(anyhow!("OCR is already in progress"));
+                }
+                reset_document_ocr_status(&self.state, document.id).await?;
+                let priority = 5; // Same priority as a single retry
+                self.state
+                    .queue_service
+                    .enqueue_document(document.id, priority, document.file_size)
+                    .await?;
+            }
+            BulkOperationKind::ChangeOwner => {
+                let owner_id = parameters.owner_id.ok_or_else(|| anyhow!("No new owner given"))?;
+                legal_hold_service::ensure_not_held(&self.state.db, document.id).await?;
+                self.state.db.transfer_document_owner(document.id, owner_id).await?;
+            }
+            BulkOperationKind::Retitle => {
+                let mode = parameters.title_mode.as_deref().unwrap_or("auto");
+                TitleService::new(self.state.db.clone())
+                    .retitle(&document, mode, parameters.include_named)
+                    .await?;
+            }
+            BulkOperationKind::Merge => return Err(anyhow!("Merges combine all documents at once")),
+        }
+
+        Ok(())
+    }
+
+    async fn merge(&self, operation: &BulkOperation, user: &User, document_ids: &[Uuid]) -> Result<Uuid> {
+        let mut documents = Vec::with_capacity(document_ids.len());
+        for document_id in document_ids {
+            let document = self
+                .state
+                .db
+                .get_document_by_id(*document_id, user.id, user.role)
+                .await?
+                .ok_or_else(|| anyhow!("Document {} not found", document_id))?;
+            documents.push(document);
+        }
+
+        let merged = merge_documents_as(
+            &self.state,
+            &documents,
+            user.id,
+            user.role,
+            operation.parameters.filename.as_deref(),
+            operation.parameters.delete_originals,
+        )
+        .await?;
+        self.state
+            .db
+            .update_bulk_operation_progress(operation.id, documents.len() as i32, 0, &[])
+            .await?;
+
+        Ok(merged.id)
+    }
+
+    async fn record_labels_change(
+        &self,
+        operation: &BulkOperation,
+        user: &User,
+        client: &ClientInfo,
+        document_id: Uuid,
+        change: &str,
+    ) {
+        audit_service::record(
+            &self.state.db,
+            Some(user),
+            client,
+            AuditEvent::new(audit_service::DOCUMENT_LABELS_CHANGE, "document", Some(document_id)).details(
+                serde_json::json!({
+                    "bulk_operation_id": operation.id,
+                    change: operation.parameters.label_ids,
+                }),
+            ),
+        )
+        .await;
+        EventService::new(self.state.db.clone()).publish_labels_changed(document_id).await;
+    }
+}
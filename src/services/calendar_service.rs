@@ -0,0 +1,141 @@
+//! iCalendar (RFC 5545) feeds of document dates, such as invoice due dates and
+//! contract renewals, for calendar apps to subscribe to.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::{CalendarEntry, CalendarFeed};
+
+/// Dates this far in the past stay in the feed, so overdue items remain visible
+pub const PAST_DAYS: i64 = 30;
+
+/// Calendars that honor it fetch the feed again after this long
+const REFRESH_INTERVAL: &str = "PT6H";
+
+/// The feed as an iCalendar document. Events are all-day, one per document date,
+/// with a reminder for each of the feed's lead times. `public_url`, when set, links
+/// each event to its document.
+pub fn render_feed(feed: &CalendarFeed, entries: &[CalendarEntry], public_url: Option<&str>, now: DateTime<Utc>) -> String {
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Readur//Document dates//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        "X-WR-CALNAME:Readur".to_string(),
+        format!("REFRESH-INTERVAL;VALUE=DURATION:{}", REFRESH_INTERVAL),
+        format!("X-PUBLISHED-TTL:{}", REFRESH_INTERVAL),
+    ];
+
+    for entry in entries {
+        let label = entry.field.replace('_', " ");
+        let mut description = format!("{} of {} ({})", label, entry.filename, entry.document_type);
+        let url = public_url.map(|base| format!("{}/documents/{}", base.trim_end_matches('/'), entry.document_id));
+        if let Some(url) = &url {
+            description.push_str(&format!("\n{}", url));
+        }
+
+        lines.push("BEGIN:VEVENT".to_string());
+        // Stable across fetches, so calendars update events instead of duplicating them
+        lines.push(format!("UID:{}-{}@readur", entry.document_id, entry.field));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", entry.date.format("%Y%m%d")));
+        lines.push(format!("DTEND;VALUE=DATE:{}", (entry.date + Duration::days(1)).format("%Y%m%d")));
+        lines.push(format!("SUMMARY:{}", escape_text(&format!("{}: {}", capitalize(&label), entry.filename))));
+        lines.push(format!("DESCRIPTION:{}", escape_text(&description)));
+        if let Some(url) = &url {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        for days in &feed.lead_days {
+            lines.push("BEGIN:VALARM".to_string());
+            lines.push("ACTION:DISPLAY".to_string());
+            lines.push(format!("DESCRIPTION:{}", escape_text(&format!("{}: {}", capitalize(&label), entry.filename))));
+            lines.push(format!("TRIGGER:-P{}D", days));
+            lines.push("END:VALARM".to_string());
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold_line(line)).collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Escapes a TEXT value: backslashes, semicolons, commas and line breaks
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Lines longer than 75 octets continue on the next line after a space, without
+/// splitting a character
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(c);
+        line_length += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_render_feed() {
+        let now = DateTime::parse_from_rfc3339("2025-02-01T08:00:00Z").unwrap().with_timezone(&Utc);
+        let feed = CalendarFeed {
+            user_id: Uuid::nil(),
+            lead_days: vec![7, 1],
+            date_fields: vec!["due_date".to_string()],
+            last_accessed_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let document_id = Uuid::new_v4();
+        let entries = vec![CalendarEntry {
+            document_id,
+            filename: "ACME, invoice; March.pdf".to_string(),
+            document_type: "Invoice".to_string(),
+            field: "due_date".to_string(),
+            date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+        }];
+
+        let ics = render_feed(&feed, &entries, Some("https://docs.example.com/"), now);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains(&format!("UID:{}-due_date@readur\r\n", document_id)));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20250301\r\nDTEND;VALUE=DATE:20250302\r\n"));
+        assert!(ics.contains("SUMMARY:Due date: ACME\\, invoice\\; March.pdf\r\n"));
+        assert_eq!(ics.matches("BEGIN:VALARM").count(), 2);
+        assert!(ics.contains("TRIGGER:-P7D\r\n") && ics.contains("TRIGGER:-P1D\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let line = format!("DESCRIPTION:{}", "ä".repeat(60));
+        let folded = fold_line(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
@@ -0,0 +1,268 @@
+//! Scans made with a phone camera: each photo is cut to the page outline the app
+//! detected, straightened into a rectangle, turned upright and given more contrast,
+//! then the pages are assembled into a PDF with a text layer.
+
+use anyhow::{anyhow, Result};
+
+use crate::routes::documents::types::{CaptureMetadata, CapturePoint};
+
+/// Photos one capture may have
+pub const MAX_CAPTURE_PAGES: usize = 50;
+/// Longest edge of a straightened page in pixels, about A4 at 300 DPI
+const MAX_PAGE_EDGE: f32 = 3508.0;
+/// Share of pixels, at each end, that may be clipped when stretching the contrast
+const CLIP_FRACTION: f64 = 0.01;
+/// Pages whose brightness spans less than this are left alone, as they are blank
+const MIN_LEVEL_SPREAD: u8 = 16;
+const PAGE_JPEG_QUALITY: u8 = 85;
+
+/// A photo of a capture could not be turned into a page
+#[derive(Debug)]
+pub struct UnusablePhoto {
+    /// 1-based
+    pub page: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for UnusablePhoto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Page {}: {}", self.page, self.reason)
+    }
+}
+
+impl std::error::Error for UnusablePhoto {}
+
+/// What is wrong with the metadata of a capture of `page_count` photos
+pub fn validate(metadata: &CaptureMetadata, page_count: usize) -> Result<(), String> {
+    if page_count == 0 {
+        return Err("No page photos found in the capture".to_string());
+    }
+    if page_count > MAX_CAPTURE_PAGES {
+        return Err(format!("A capture may have at most {} pages", MAX_CAPTURE_PAGES));
+    }
+    if metadata.pages.len() > page_count {
+        return Err(format!("Metadata describes {} pages, but {} photos were sent", metadata.pages.len(), page_count));
+    }
+    for (index, page) in metadata.pages.iter().enumerate() {
+        if ![0, 90, 180, 270].contains(&page.rotation) {
+            return Err(format!("Page {}: rotation must be 0, 90, 180 or 270", index + 1));
+        }
+        if let Some(corners) = &page.corners {
+            if !is_page_outline(corners) {
+                return Err(format!(
+                    "Page {}: corners must be four points between 0 and 1, clockwise from the top left",
+                    index + 1
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether four points in fractions of the photo outline a convex page, clockwise
+/// with y pointing down. Scaling to pixels keeps both properties.
+fn is_page_outline(corners: &[CapturePoint]) -> bool {
+    if corners.len() != 4 || !corners.iter().all(|p| (0.0..=1.0).contains(&p.x) && (0.0..=1.0).contains(&p.y)) {
+        return false;
+    }
+    (0..4).all(|i| {
+        let (a, b, c) = (corners[i], corners[(i + 1) % 4], corners[(i + 2) % 4]);
+        (b.x - a.x) * (c.y - b.y) - (b.y - a.y) * (c.x - b.x) > 0.0
+    })
+}
+
+/// Corners in pixels of a `width` × `height` photo
+fn outline_pixels(corners: &[CapturePoint], width: u32, height: u32) -> [(f32, f32); 4] {
+    let mut quad = [(0.0, 0.0); 4];
+    for (point, corner) in quad.iter_mut().zip(corners) {
+        *point = (corner.x * width as f32, corner.y * height as f32);
+    }
+    quad
+}
+
+/// Size of the straightened page: its longer top or bottom edge by its longer left
+/// or right edge, scaled down to fit [`MAX_PAGE_EDGE`]
+fn straightened_size(quad: &[(f32, f32); 4]) -> (u32, u32) {
+    let distance = |a: (f32, f32), b: (f32, f32)| ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt();
+    let width = distance(quad[0], quad[1]).max(distance(quad[3], quad[2]));
+    let height = distance(quad[0], quad[3]).max(distance(quad[1], quad[2]));
+    let scale = (MAX_PAGE_EDGE / width.max(height)).min(1.0);
+    (((width * scale).round() as u32).max(1), ((height * scale).round() as u32).max(1))
+}
+
+/// The darkest and brightest levels to stretch to black and white, from a histogram
+/// of brightness; None when the page is too even to stretch
+fn stretch_levels(histogram: &[u64; 256]) -> Option<(u8, u8)> {
+    let total: u64 = histogram.iter().sum();
+    let clip = (total as f64 * CLIP_FRACTION) as u64;
+    let low = clipped_level(histogram, clip, 0..256);
+    let high = clipped_level(histogram, clip, (0..256).rev());
+    (high > low && high - low >= MIN_LEVEL_SPREAD).then_some((low, high))
+}
+
+/// The first of `levels` at which more than `clip` pixels have been passed
+fn clipped_level(histogram: &[u64; 256], clip: u64, mut levels: impl Iterator<Item = usize>) -> u8 {
+    let mut seen = 0;
+    levels
+        .find(|&level| {
+            seen += histogram[level];
+            seen > clip
+        })
+        .unwrap_or(0) as u8
+}
+
+/// Straighten, rotate and enhance one photo of a capture
+#[cfg(feature = "ocr")]
+pub fn process_page(
+    photo: &[u8],
+    page: Option<&crate::routes::documents::types::CapturePageMetadata>,
+    enhance: bool,
+) -> Result<image::RgbImage> {
+    use image::imageops;
+    use imageproc::geometric_transformations::{warp_into, Interpolation, Projection};
+
+    let photo = image::load_from_memory(photo).map_err(|e| anyhow!("Photo could not be read: {}", e))?.to_rgb8();
+    let mut pixels = match page.and_then(|page| page.corners.as_deref()) {
+        Some(corners) => {
+            let quad = outline_pixels(corners, photo.width(), photo.height());
+            let (width, height) = straightened_size(&quad);
+            let target = [(0.0, 0.0), (width as f32, 0.0), (width as f32, height as f32), (0.0, height as f32)];
+            let projection =
+                Projection::from_control_points(quad, target).ok_or_else(|| anyhow!("Page outline cannot be straightened"))?;
+            let mut straightened = image::RgbImage::new(width, height);
+            warp_into(&photo, &projection, Interpolation::Bilinear, image::Rgb([255, 255, 255]), &mut straightened);
+            straightened
+        }
+        None => photo,
+    };
+
+    pixels = match page.map(|page| page.rotation).unwrap_or(0) {
+        90 => imageops::rotate90(&pixels),
+        180 => imageops::rotate180(&pixels),
+        270 => imageops::rotate270(&pixels),
+        _ => pixels,
+    };
+    if enhance {
+        stretch_contrast(&mut pixels);
+    }
+    Ok(pixels)
+}
+
+/// Map the darkest and brightest levels of the page to black and white, so paper
+/// photographed in dim light comes out white again
+#[cfg(feature = "ocr")]
+fn stretch_contrast(pixels: &mut image::RgbImage) {
+    let brightness = |p: &image::Rgb<u8>| ((299 * p[0] as u32 + 587 * p[1] as u32 + 114 * p[2] as u32) / 1000) as usize;
+    let mut histogram = [0u64; 256];
+    for pixel in pixels.pixels() {
+        histogram[brightness(pixel)] += 1;
+    }
+    let Some((low, high)) = stretch_levels(&histogram) else {
+        return;
+    };
+
+    let spread = (high - low) as f32;
+    let mut table = [0u8; 256];
+    for (level, value) in table.iter_mut().enumerate() {
+        *value = (((level as f32 - low as f32) / spread) * 255.0).round().clamp(0.0, 255.0) as u8;
+    }
+    for pixel in pixels.pixels_mut() {
+        for channel in pixel.0.iter_mut() {
+            *channel = table[*channel as usize];
+        }
+    }
+}
+
+/// Turn the photos of a capture, in page order, into a PDF with a text layer in
+/// `language`
+#[cfg(feature = "ocr")]
+pub async fn assemble(photos: Vec<Vec<u8>>, metadata: &CaptureMetadata, language: &str) -> Result<Vec<u8>> {
+    use crate::ocr::page_images;
+
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let work_dir = std::path::PathBuf::from(temp_dir).join(format!("capture_{}", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&work_dir).await?;
+
+    let result: Result<Vec<u8>> = async {
+        let enhance = metadata.enhance.unwrap_or(true);
+        let mut page_files = Vec::with_capacity(photos.len());
+        for (index, photo) in photos.into_iter().enumerate() {
+            let page = metadata.pages.get(index).cloned();
+            let page_file = work_dir.join(format!("page-{:05}.jpg", index + 1));
+            let path = page_file.clone();
+            // Decoding and warping a phone photo takes a while
+            tokio::task::spawn_blocking(move || -> Result<()> {
+                let pixels = process_page(&photo, page.as_ref(), enhance)
+                    .map_err(|e| UnusablePhoto { page: index + 1, reason: e.to_string() })?;
+                let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                image::codecs::jpeg::JpegEncoder::new_with_quality(&mut file, PAGE_JPEG_QUALITY).encode_image(&pixels)?;
+                Ok(())
+            })
+            .await??;
+            page_files.push(page_file);
+        }
+        page_images::images_to_pdf(&page_files, language).await
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&work_dir).await;
+    result
+}
+
+#[cfg(not(feature = "ocr"))]
+pub async fn assemble(_photos: Vec<Vec<u8>>, _metadata: &CaptureMetadata, _language: &str) -> Result<Vec<u8>> {
+    Err(anyhow!("Capture uploads require OCR feature"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::documents::types::CapturePageMetadata;
+
+    fn outline(points: [(f32, f32); 4]) -> Vec<CapturePoint> {
+        points.iter().map(|&(x, y)| CapturePoint { x, y }).collect()
+    }
+
+    #[test]
+    fn test_validate_page_outlines() {
+        let page = |corners, rotation| CapturePageMetadata { corners: Some(corners), rotation };
+        let clockwise = outline([(0.1, 0.1), (0.9, 0.15), (0.85, 0.9), (0.12, 0.95)]);
+        let metadata = CaptureMetadata { pages: vec![page(clockwise.clone(), 90)], ..Default::default() };
+        assert!(validate(&metadata, 2).is_ok());
+        assert!(validate(&metadata, 0).is_err());
+        assert!(validate(&CaptureMetadata::default(), MAX_CAPTURE_PAGES + 1).is_err());
+
+        let mut reversed = clockwise.clone();
+        reversed.reverse();
+        assert!(validate(&CaptureMetadata { pages: vec![page(reversed, 0)], ..Default::default() }, 1).is_err());
+        let crossed = outline([(0.1, 0.1), (0.9, 0.9), (0.9, 0.1), (0.1, 0.9)]);
+        assert!(validate(&CaptureMetadata { pages: vec![page(crossed, 0)], ..Default::default() }, 1).is_err());
+        let outside = outline([(0.1, 0.1), (1.2, 0.1), (0.9, 0.9), (0.1, 0.9)]);
+        assert!(validate(&CaptureMetadata { pages: vec![page(outside, 0)], ..Default::default() }, 1).is_err());
+        assert!(validate(&CaptureMetadata { pages: vec![page(clockwise, 45)], ..Default::default() }, 1).is_err());
+    }
+
+    #[test]
+    fn test_straightened_size() {
+        let corners = outline([(0.1, 0.1), (0.6, 0.1), (0.6, 0.8), (0.1, 0.8)]);
+        let quad = outline_pixels(&corners, 1000, 1000);
+        assert_eq!(straightened_size(&quad), (500, 700));
+        // A 48 megapixel photo is scaled down to the longest edge
+        let quad = outline_pixels(&outline([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]), 6000, 8000);
+        assert_eq!(straightened_size(&quad), (2631, 3508));
+    }
+
+    #[test]
+    fn test_stretch_levels() {
+        let mut histogram = [0u64; 256];
+        histogram[0] = 1;
+        histogram[60] = 300;
+        histogram[200] = 700;
+        histogram[255] = 1;
+        assert_eq!(stretch_levels(&histogram), Some((60, 200)));
+
+        let mut blank = [0u64; 256];
+        blank[240] = 1000;
+        blank[245] = 1000;
+        assert_eq!(stretch_levels(&blank), None);
+    }
+}
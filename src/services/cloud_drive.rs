@@ -0,0 +1,192 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use thiserror::Error;
+
+/// Access tokens are refreshed this long before they expire
+const TOKEN_EXPIRY_MARGIN_SECS: i64 = 120;
+
+/// A file reported by a provider's change feed
+#[derive(Debug, Clone)]
+pub struct CloudFile {
+    /// Provider's stable id, used to download the file
+    pub id: String,
+    /// Path relative to the source's root folder, `/`-separated without a leading slash
+    pub relative_path: String,
+    /// Path as the provider displays it, stored as the document's source path
+    pub display_path: String,
+    pub name: String,
+    pub size: i64,
+    pub modified_at: Option<DateTime<Utc>>,
+    pub content_hash: Option<String>,
+}
+
+/// One page of changes. `cursor` resumes after this page; once `has_more` is false
+/// it is the cursor for the next sync.
+#[derive(Debug, Clone)]
+pub struct DeltaPage {
+    pub files: Vec<CloudFile>,
+    pub deleted_count: usize,
+    pub cursor: String,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct OAuthToken {
+    pub access_token: String,
+    pub expires_at: DateTime<Utc>,
+    /// Set when the provider rotated the refresh token
+    pub refresh_token: Option<String>,
+}
+
+impl OAuthToken {
+    pub fn from_expires_in(access_token: String, expires_in_secs: i64, refresh_token: Option<String>) -> Self {
+        Self {
+            access_token,
+            expires_at: Utc::now() + chrono::Duration::seconds(expires_in_secs),
+            refresh_token,
+        }
+    }
+}
+
+pub fn token_is_fresh(expires_at: DateTime<Utc>) -> bool {
+    expires_at - chrono::Duration::seconds(TOKEN_EXPIRY_MARGIN_SECS) > Utc::now()
+}
+
+/// Provider responses the sync recovers from, returned inside `anyhow::Error`
+#[derive(Debug, Error)]
+pub enum CloudDriveError {
+    #[error("access token was rejected")]
+    Unauthorized,
+    #[error("delta cursor is no longer valid, a full listing is required")]
+    CursorExpired,
+}
+
+pub fn is_unauthorized(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<CloudDriveError>(), Some(CloudDriveError::Unauthorized))
+}
+
+pub fn is_cursor_expired(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<CloudDriveError>(), Some(CloudDriveError::CursorExpired))
+}
+
+/// A cloud drive with an incremental change feed
+#[async_trait]
+pub trait CloudDriveClient: Send + Sync {
+    /// Name used in logs and errors
+    fn provider_name(&self) -> &'static str;
+
+    async fn refresh_access_token(&self, refresh_token: &str) -> Result<OAuthToken>;
+
+    /// Changes since `cursor`, or a full listing of the root folder when None
+    async fn delta(&self, access_token: &str, cursor: Option<&str>) -> Result<DeltaPage>;
+
+    async fn download(&self, access_token: &str, file: &CloudFile) -> Result<Vec<u8>>;
+
+    /// Describes the account or drive the token gives access to
+    async fn describe(&self, access_token: &str) -> Result<String>;
+}
+
+/// Check that the refresh token is accepted and describe what it gives access to
+pub async fn test_connection(client: &dyn CloudDriveClient, refresh_token: &str) -> Result<String> {
+    let token = client.refresh_access_token(refresh_token).await?;
+    client.describe(&token.access_token).await
+}
+
+/// Include/exclude glob patterns matched against a file's path relative to the
+/// source root. No include patterns means everything is included; excludes win.
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: build_glob_set(include)?,
+            exclude: build_glob_set(exclude)?,
+        })
+    }
+
+    pub fn matches(&self, relative_path: &str) -> bool {
+        let path = relative_path.trim_start_matches('/');
+        if self.exclude.as_ref().is_some_and(|set| set.is_match(path)) {
+            return false;
+        }
+        match &self.include {
+            Some(set) => set.is_match(path),
+            None => true,
+        }
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern.trim_start_matches('/'))
+            .map_err(|e| anyhow::anyhow!("Invalid glob pattern '{}': {}", pattern, e))?;
+        builder.add(glob);
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// `path` relative to `root` (both as the provider spells them, compared
+/// case-insensitively), or None when it lies outside the root
+pub fn relative_to_root(path: &str, root: &str) -> Option<String> {
+    let path = path.trim_matches('/');
+    let root = root.trim_matches('/');
+    if root.is_empty() {
+        return Some(path.to_string());
+    }
+
+    let prefix_len = root.len();
+    if path.len() > prefix_len
+        && path.is_char_boundary(prefix_len)
+        && path[..prefix_len].eq_ignore_ascii_case(root)
+        && path.as_bytes()[prefix_len] == b'/'
+    {
+        Some(path[prefix_len + 1..].to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let everything = PathFilter::new(&[], &[]).unwrap();
+        assert!(everything.matches("a/b/c.pdf"));
+
+        let filter = PathFilter::new(
+            &patterns(&["invoices/**/*.pdf", "*.png"]),
+            &patterns(&["**/drafts/**"]),
+        ).unwrap();
+        assert!(filter.matches("invoices/2024/march.pdf"));
+        assert!(filter.matches("/invoices/april.pdf"));
+        assert!(filter.matches("scan.png"));
+        assert!(!filter.matches("invoices/drafts/may.pdf"));
+        assert!(!filter.matches("contracts/lease.pdf"));
+
+        assert!(PathFilter::new(&patterns(&["a[b"]), &[]).is_err());
+    }
+
+    #[test]
+    fn test_relative_to_root() {
+        assert_eq!(relative_to_root("/Scans/2024/a.pdf", ""), Some("Scans/2024/a.pdf".to_string()));
+        assert_eq!(relative_to_root("/Scans/2024/a.pdf", "/scans"), Some("2024/a.pdf".to_string()));
+        assert_eq!(relative_to_root("/Scans2/a.pdf", "/Scans"), None);
+        assert_eq!(relative_to_root("/Other/a.pdf", "/Scans/"), None);
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::mime_detection;
+use crate::models::Document;
+use crate::ocr::pdf_pages;
+use crate::services::file_service::FileService;
+use crate::services::office_preview;
+
+/// Documents a single export may contain
+pub const MAX_EXPORT_DOCUMENTS: usize = 500;
+
+/// A collection rendered as one PDF
+pub struct CollectionExport {
+    pub pdf: Vec<u8>,
+    /// Documents in the PDF
+    pub included: usize,
+    /// Documents that have no PDF rendition, or whose rendition failed
+    pub skipped: Vec<Uuid>,
+}
+
+/// Merges the documents of a collection into a single PDF, in reading order. PDFs go
+/// in as they are, office documents as their preview rendition and, with the `ocr`
+/// feature, images as a page with a text layer. Other documents are skipped.
+pub struct CollectionExportService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl CollectionExportService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Merge the documents, in the given order. `language` is the OCR language of the
+    /// text layer of images. None when no document could be rendered as PDF.
+    pub async fn export(&self, document_ids: &[Uuid], language: &str) -> Result<Option<CollectionExport>> {
+        if document_ids.len() > MAX_EXPORT_DOCUMENTS {
+            return Err(anyhow!("At most {} documents can be exported at once", MAX_EXPORT_DOCUMENTS));
+        }
+        let mut documents: HashMap<Uuid, Document> = self
+            .db
+            .get_documents_by_ids(document_ids)
+            .await?
+            .into_iter()
+            .map(|document| (document.id, document))
+            .collect();
+
+        let mut parts = Vec::with_capacity(document_ids.len());
+        let mut skipped = Vec::new();
+        for id in document_ids {
+            let Some(document) = documents.remove(id) else {
+                continue;
+            };
+            match self.render(&document, language).await {
+                Ok(Some(pdf)) => parts.push(pdf),
+                Ok(None) => skipped.push(document.id),
+                Err(e) => {
+                    warn!("Leaving document {} out of the collection export: {}", document.id, e);
+                    skipped.push(document.id);
+                }
+            }
+        }
+        if parts.is_empty() {
+            return Ok(None);
+        }
+
+        let included = parts.len();
+        let pdf = if included == 1 {
+            parts.remove(0)
+        } else {
+            pdf_pages::concatenate(&parts).await?
+        };
+        info!("Exported {} documents as one PDF, skipped {}", included, skipped.len());
+
+        Ok(Some(CollectionExport { pdf, included, skipped }))
+    }
+
+    /// The document as PDF; None when it has no PDF rendition
+    async fn render(&self, document: &Document, language: &str) -> Result<Option<Vec<u8>>> {
+        let extension = mime_detection::rendering_extension(&document.original_filename, &document.mime_type);
+        if extension == "pdf" {
+            return Ok(Some(self.file_service.read_file(&document.file_path).await?));
+        }
+        if office_preview::is_office_extension(&extension) {
+            let pdf = self
+                .file_service
+                .get_or_generate_preview_pdf(&document.file_path, &document.original_filename, &document.mime_type)
+                .await?;
+            return Ok(Some(pdf));
+        }
+        if document.mime_type.starts_with("image/") {
+            let data = self.file_service.read_file(&document.file_path).await?;
+            return image_to_pdf(&data, &extension, language).await;
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "ocr")]
+async fn image_to_pdf(data: &[u8], extension: &str, language: &str) -> Result<Option<Vec<u8>>> {
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let path = std::path::PathBuf::from(temp_dir).join(format!("collection_page_{}.{}", Uuid::new_v4(), extension));
+    tokio::fs::write(&path, data).await?;
+
+    let result = crate::ocr::page_images::images_to_pdf(std::slice::from_ref(&path), language).await;
+    let _ = tokio::fs::remove_file(&path).await;
+    result.map(Some)
+}
+
+#[cfg(not(feature = "ocr"))]
+async fn image_to_pdf(_data: &[u8], _extension: &str, _language: &str) -> Result<Option<Vec<u8>>> {
+    Ok(None)
+}
@@ -0,0 +1,634 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::ingestion::document_ingestion::{DocumentIngestionService, IngestionResult};
+use crate::models::{
+    ConsistencyIssue, ConsistencyIssueKind, ConsistencyRepairAction, ConsistencyRepairRequest,
+    ConsistencyRepairResult, ConsistencyReport, CreateNotification, UserRole,
+};
+use crate::ocr::queue::OcrQueueService;
+use crate::services::file_service::FileService;
+use crate::services::i18n;
+
+/// Upload subdirectory that orphaned files are moved to by auto-repair
+pub const QUARANTINE_DIR: &str = "orphaned";
+
+/// Queue priority of documents sent to OCR again by a repair, as for a manual retry
+const REPAIR_OCR_PRIORITY: i32 = 5;
+
+static LAST_REPORT: Lazy<RwLock<Option<ConsistencyReport>>> = Lazy::new(|| RwLock::new(None));
+
+/// What the startup consistency check does, from `CONSISTENCY_CHECK_ON_STARTUP`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsistencyCheckMode {
+    Off,
+    Report,
+    Repair,
+}
+
+impl ConsistencyCheckMode {
+    pub fn from_env() -> Self {
+        std::env::var("CONSISTENCY_CHECK_ON_STARTUP")
+            .map(|v| Self::parse(&v))
+            .unwrap_or(Self::Off)
+    }
+
+    fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "repair" => Self::Repair,
+            "report" | "true" | "1" => Self::Report,
+            _ => Self::Off,
+        }
+    }
+}
+
+/// When the scheduled consistency check runs, from `CONSISTENCY_CHECK_INTERVAL_HOURS`
+/// and `CONSISTENCY_CHECK_VERIFY_HASHES`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsistencySchedule {
+    pub interval_hours: u64,
+    pub verify_hashes: bool,
+}
+
+impl ConsistencySchedule {
+    /// `None` when no interval is set, or it is 0
+    pub fn from_env() -> Option<Self> {
+        let interval_hours = std::env::var("CONSISTENCY_CHECK_INTERVAL_HOURS")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|hours| *hours > 0)?;
+        let verify_hashes = std::env::var("CONSISTENCY_CHECK_VERIFY_HASHES")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        Some(Self { interval_hours, verify_hashes })
+    }
+}
+
+/// Why a repair was not made
+#[derive(Debug)]
+pub enum RepairError {
+    /// The request does not name what the action needs
+    Invalid(String),
+    /// The document or file does not exist
+    NotFound,
+    /// The issue no longer holds, e.g. a document now refers to the file
+    Conflict(String),
+    Failed(anyhow::Error),
+}
+
+impl From<anyhow::Error> for RepairError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Failed(e)
+    }
+}
+
+/// Cross-checks documents, stored files, the knowledge graph and the OCR queue.
+/// Auto-repair never deletes user data: documents with a missing file are marked
+/// failed, and orphaned files are moved to a quarantine directory.
+pub struct ConsistencyService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl ConsistencyService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Report of the most recent check since startup
+    pub async fn last_report() -> Option<ConsistencyReport> {
+        LAST_REPORT.read().await.clone()
+    }
+
+    /// Check everything once. Reading files back to verify their hashes is slow on
+    /// large libraries, so it is only done when asked for.
+    pub async fn run(&self, repair: bool, verify_hashes: bool) -> Result<ConsistencyReport> {
+        let started_at = Utc::now();
+        let mut issues = Vec::new();
+
+        let documents = self.db.get_document_file_paths().await?;
+        let mut missing = HashSet::new();
+        for (document_id, file_path) in &documents {
+            if self.file_service.file_exists(file_path).await {
+                continue;
+            }
+            missing.insert(*document_id);
+            let repaired = repair && self.db.mark_document_file_missing(*document_id).await.is_ok();
+            issues.push(ConsistencyIssue {
+                kind: ConsistencyIssueKind::MissingFile,
+                document_id: Some(*document_id),
+                record_id: None,
+                path: Some(file_path.clone()),
+                detail: "Document file not found in storage".to_string(),
+                repaired,
+            });
+        }
+
+        let hashes_checked = if verify_hashes {
+            self.check_hashes(&missing, &mut issues).await?
+        } else {
+            0
+        };
+
+        let files_checked = if self.file_service.is_s3_enabled() {
+            0
+        } else {
+            self.check_orphaned_files(&documents, repair, &mut issues).await?
+        };
+
+        let edges = self.db.find_dangling_graph_edges().await?;
+        let edges_repaired = if repair && !edges.is_empty() {
+            let ids: Vec<Uuid> = edges.iter().map(|(id, _)| *id).collect();
+            self.db.delete_graph_edges(&ids).await? > 0
+        } else {
+            false
+        };
+        issues.extend(edges.into_iter().map(|(edge_id, document_id)| ConsistencyIssue {
+            kind: ConsistencyIssueKind::DanglingGraphEdge,
+            document_id: Some(document_id),
+            record_id: Some(edge_id),
+            path: None,
+            detail: "Edge references a node that is missing or belongs to another document".to_string(),
+            repaired: edges_repaired,
+        }));
+
+        let entries = self.db.find_orphaned_queue_entries().await?;
+        let entries_repaired = if repair && !entries.is_empty() {
+            let ids: Vec<Uuid> = entries.iter().map(|(id, _)| *id).collect();
+            self.db.delete_queue_entries(&ids).await? > 0
+        } else {
+            false
+        };
+        issues.extend(entries.into_iter().map(|(entry_id, document_id)| ConsistencyIssue {
+            kind: ConsistencyIssueKind::OrphanedQueueEntry,
+            document_id,
+            record_id: Some(entry_id),
+            path: None,
+            detail: "OCR queue entry references a deleted document".to_string(),
+            repaired: entries_repaired,
+        }));
+
+        let report = ConsistencyReport {
+            started_at,
+            finished_at: Utc::now(),
+            repair,
+            documents_checked: documents.len() as i64,
+            files_checked,
+            hashes_checked,
+            repaired_count: issues.iter().filter(|i| i.repaired).count() as i64,
+            issues,
+        };
+
+        if report.issues.is_empty() {
+            info!("Consistency check found no issues ({} documents, {} files)", report.documents_checked, report.files_checked);
+        } else {
+            warn!(
+                "Consistency check found {} issues, {} repaired",
+                report.issues.len(),
+                report.repaired_count
+            );
+        }
+
+        *LAST_REPORT.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Check on the schedule, reporting without repairing, and notify admins when a
+    /// check finds issues the previous one did not
+    pub async fn run_scheduled(self, schedule: ConsistencySchedule) {
+        let mut interval = tokio::time::interval(Duration::from_secs(schedule.interval_hours * 3600));
+        // The first tick completes at once; the startup check covers that moment
+        interval.tick().await;
+        let mut previous: Option<ConsistencyReport> = None;
+        loop {
+            interval.tick().await;
+            match self.run(false, schedule.verify_hashes).await {
+                Ok(report) => {
+                    if count_new_issues(previous.as_ref(), &report) > 0 {
+                        self.notify_admins(&report).await;
+                    }
+                    previous = Some(report);
+                }
+                Err(e) => error!("Scheduled consistency check failed: {}", e),
+            }
+        }
+    }
+
+    async fn notify_admins(&self, report: &ConsistencyReport) {
+        let values = [("issues", summarize_issues(&report.issues))];
+        let users = match self.db.get_all_users().await {
+            Ok(users) => users,
+            Err(e) => {
+                warn!("Failed to notify admins of consistency issues: {}", e);
+                return;
+            }
+        };
+        for admin in users.iter().filter(|user| user.role == UserRole::Admin) {
+            let locale = i18n::locale_of(&self.db, admin.id).await;
+            let notification = CreateNotification {
+                metadata: Some(serde_json::json!({
+                    "issues": report.issues.len(),
+                    "finished_at": report.finished_at,
+                })),
+                ..i18n::notification(&locale, "warning", "notification.consistency_issues", &values)
+            };
+            if let Err(e) = self.db.create_notification(admin.id, &notification).await {
+                warn!("Failed to notify admin {} of consistency issues: {}", admin.id, e);
+            }
+        }
+    }
+
+    /// Read each document's file back and compare it with the hash recorded when it
+    /// was ingested. Documents already found missing are skipped.
+    async fn check_hashes(&self, missing: &HashSet<Uuid>, issues: &mut Vec<ConsistencyIssue>) -> Result<i64> {
+        let mut hashes_checked = 0;
+        for (document_id, file_path, expected) in self.db.get_document_file_hashes().await? {
+            if missing.contains(&document_id) {
+                continue;
+            }
+            let data = match self.file_service.read_file(&file_path).await {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Failed to read {} to verify its hash: {}", file_path, e);
+                    continue;
+                }
+            };
+            hashes_checked += 1;
+
+            let actual = sha256_hex(&data);
+            if !actual.eq_ignore_ascii_case(&expected) {
+                issues.push(ConsistencyIssue {
+                    kind: ConsistencyIssueKind::HashMismatch,
+                    document_id: Some(document_id),
+                    record_id: None,
+                    path: Some(file_path),
+                    detail: format!(
+                        "Stored file has hash {}, {} was recorded at ingestion",
+                        short_hash(&actual),
+                        short_hash(&expected)
+                    ),
+                    repaired: false,
+                });
+            }
+        }
+        Ok(hashes_checked)
+    }
+
+    /// Fix one issue of a report. `user_id` owns documents created by re-ingesting an
+    /// orphaned file when the request names no owner.
+    pub async fn repair(
+        &self,
+        request: &ConsistencyRepairRequest,
+        user_id: Uuid,
+        queue_service: &OcrQueueService,
+    ) -> std::result::Result<ConsistencyRepairResult, RepairError> {
+        match (request.action, request.document_id, request.path.as_deref()) {
+            (ConsistencyRepairAction::MarkMissing, Some(document_id), _) => self.mark_missing(document_id).await,
+            (ConsistencyRepairAction::MarkMissing, None, _) => {
+                Err(RepairError::Invalid("mark_missing needs a document_id".to_string()))
+            }
+            (ConsistencyRepairAction::Reingest, Some(document_id), _) => {
+                self.accept_stored_file(document_id, queue_service).await
+            }
+            (ConsistencyRepairAction::Reingest, None, Some(path)) => {
+                self.reingest_orphan(path, request.user_id.unwrap_or(user_id), queue_service).await
+            }
+            (ConsistencyRepairAction::Reingest, None, None) => {
+                Err(RepairError::Invalid("reingest needs a document_id or a path".to_string()))
+            }
+            (ConsistencyRepairAction::DeleteOrphan, _, Some(path)) => self.delete_orphan(path).await,
+            (ConsistencyRepairAction::DeleteOrphan, _, None) => {
+                Err(RepairError::Invalid("delete_orphan needs a path".to_string()))
+            }
+        }
+    }
+
+    async fn mark_missing(&self, document_id: Uuid) -> std::result::Result<ConsistencyRepairResult, RepairError> {
+        let (file_path, _) = self.db.get_document_file(document_id).await?.ok_or(RepairError::NotFound)?;
+        if self.file_service.file_exists(&file_path).await {
+            return Err(RepairError::Conflict("The document's file is in storage".to_string()));
+        }
+        self.db.mark_document_file_missing(document_id).await?;
+
+        Ok(ConsistencyRepairResult {
+            action: ConsistencyRepairAction::MarkMissing,
+            document_id: Some(document_id),
+            path: Some(file_path),
+            detail: "Document marked as missing its file".to_string(),
+        })
+    }
+
+    /// Take the file as now stored to be the document's content: record its hash and
+    /// run OCR on it again
+    async fn accept_stored_file(
+        &self,
+        document_id: Uuid,
+        queue_service: &OcrQueueService,
+    ) -> std::result::Result<ConsistencyRepairResult, RepairError> {
+        let (file_path, file_size) = self.db.get_document_file(document_id).await?.ok_or(RepairError::NotFound)?;
+        let data = self.file_service.read_file(&file_path).await.map_err(|e| {
+            RepairError::Conflict(format!("The document's file cannot be read ({}); mark it missing instead", e))
+        })?;
+        self.db.update_document_file_hash(document_id, &sha256_hex(&data)).await?;
+        queue_service.enqueue_document(document_id, REPAIR_OCR_PRIORITY, file_size).await?;
+
+        Ok(ConsistencyRepairResult {
+            action: ConsistencyRepairAction::Reingest,
+            document_id: Some(document_id),
+            path: Some(file_path),
+            detail: "Recorded the hash of the stored file and queued the document for OCR".to_string(),
+        })
+    }
+
+    /// Ingest an orphaned file as a new document of `user_id`, then delete the orphan
+    async fn reingest_orphan(
+        &self,
+        path: &str,
+        user_id: Uuid,
+        queue_service: &OcrQueueService,
+    ) -> std::result::Result<ConsistencyRepairResult, RepairError> {
+        let path = self.orphaned_file(path).await?;
+        if self.db.get_user_by_id(user_id).await?.is_none() {
+            return Err(RepairError::Invalid(format!("User {} does not exist", user_id)));
+        }
+        let data = self.file_service.read_file(&path.to_string_lossy()).await?;
+        let filename = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let ingestion = DocumentIngestionService::new(self.db.clone(), self.file_service.clone());
+        let result = ingestion
+            .ingest_upload(&filename, data, "application/octet-stream", user_id)
+            .await
+            .map_err(|e| RepairError::Failed(anyhow!(e)))?;
+        let (document_id, detail) = match result {
+            IngestionResult::Created(document) | IngestionResult::NewVersion(document) => {
+                queue_service.enqueue_document(document.id, REPAIR_OCR_PRIORITY, document.file_size).await?;
+                (document.id, "Ingested the file as a new document".to_string())
+            }
+            IngestionResult::ExistingDocument(document) => {
+                (document.id, "The file's content is already stored as a document".to_string())
+            }
+            IngestionResult::Skipped { existing_document_id, .. }
+            | IngestionResult::TrackedAsDuplicate { existing_document_id } => {
+                (existing_document_id, "The file's content is already stored as a document".to_string())
+            }
+        };
+
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            warn!("Failed to remove re-ingested orphaned file {}: {}", path.display(), e);
+        }
+        Ok(ConsistencyRepairResult {
+            action: ConsistencyRepairAction::Reingest,
+            document_id: Some(document_id),
+            path: Some(path.to_string_lossy().to_string()),
+            detail,
+        })
+    }
+
+    async fn delete_orphan(&self, path: &str) -> std::result::Result<ConsistencyRepairResult, RepairError> {
+        let path = self.orphaned_file(path).await?;
+        tokio::fs::remove_file(&path).await.map_err(anyhow::Error::from)?;
+        info!("Deleted orphaned file {}", path.display());
+
+        Ok(ConsistencyRepairResult {
+            action: ConsistencyRepairAction::DeleteOrphan,
+            document_id: None,
+            path: Some(path.to_string_lossy().to_string()),
+            detail: "Deleted the orphaned file".to_string(),
+        })
+    }
+
+    /// `path` if it is a regular file, directly in a directory the check scans or in
+    /// quarantine, named after a document id that no document or version has
+    async fn orphaned_file(&self, path: &str) -> std::result::Result<PathBuf, RepairError> {
+        if self.file_service.is_s3_enabled() {
+            return Err(RepairError::Invalid("Orphaned files are only looked for on local storage".to_string()));
+        }
+        let path = PathBuf::from(path);
+        let metadata = tokio::fs::symlink_metadata(&path).await.map_err(|_| RepairError::NotFound)?;
+        if !metadata.is_file() {
+            return Err(RepairError::Invalid(format!("{} is not a regular file", path.display())));
+        }
+
+        let parent = tokio::fs::canonicalize(path.parent().unwrap_or(Path::new(".")))
+            .await
+            .map_err(|_| RepairError::NotFound)?;
+        let mut in_storage = false;
+        for dir in [
+            self.file_service.get_documents_path(),
+            self.file_service.get_thumbnails_path(),
+            self.file_service.get_subdirectory_path(QUARANTINE_DIR),
+        ] {
+            if tokio::fs::canonicalize(&dir).await.is_ok_and(|dir| dir == parent) {
+                in_storage = true;
+            }
+        }
+        if !in_storage {
+            return Err(RepairError::Invalid(format!("{} is not in document storage", path.display())));
+        }
+
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let Some(document_id) = document_id_from_file_name(&name) else {
+            return Err(RepairError::Invalid(format!("{} is not named after a document", name)));
+        };
+        if self.db.is_stored_file_referenced(document_id, &name).await? {
+            return Err(RepairError::Conflict(format!("A document refers to {}", name)));
+        }
+        Ok(path)
+    }
+
+    /// Files in the documents and thumbnails directories that belong to no document
+    async fn check_orphaned_files(
+        &self,
+        documents: &[(Uuid, String)],
+        repair: bool,
+        issues: &mut Vec<ConsistencyIssue>,
+    ) -> Result<i64> {
+        let mut known_ids: HashSet<Uuid> = documents.iter().map(|(id, _)| *id).collect();
+        // Files of earlier document versions are named after the version
+        known_ids.extend(self.db.get_document_version_ids().await?);
+        let known_names: HashSet<String> = documents
+            .iter()
+            .filter_map(|(_, path)| Path::new(path).file_name().map(|n| n.to_string_lossy().to_string()))
+            .collect();
+        let quarantine = self.file_service.get_subdirectory_path(QUARANTINE_DIR);
+
+        let mut files_checked = 0;
+        for dir in [self.file_service.get_documents_path(), self.file_service.get_thumbnails_path()] {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            while let Some(entry) = entries.next_entry().await? {
+                if !entry.file_type().await?.is_file() {
+                    continue;
+                }
+                files_checked += 1;
+
+                let name = entry.file_name().to_string_lossy().to_string();
+                // Files not named after a document id predate the structured layout; leave them alone
+                let Some(document_id) = document_id_from_file_name(&name) else {
+                    continue;
+                };
+                if known_ids.contains(&document_id) || known_names.contains(&name) {
+                    continue;
+                }
+
+                let repaired = repair && quarantine_file(&entry.path(), &quarantine).await.is_ok();
+                issues.push(ConsistencyIssue {
+                    kind: ConsistencyIssueKind::OrphanedFile,
+                    document_id: Some(document_id),
+                    record_id: None,
+                    path: Some(entry.path().to_string_lossy().to_string()),
+                    detail: "No document refers to this file".to_string(),
+                    repaired,
+                });
+            }
+        }
+
+        Ok(files_checked)
+    }
+}
+
+async fn quarantine_file(path: &Path, quarantine: &Path) -> Result<()> {
+    tokio::fs::create_dir_all(quarantine).await?;
+    let target = quarantine.join(path.file_name().unwrap_or_default());
+    tokio::fs::rename(path, &target).await?;
+    info!("Moved orphaned file {} to {}", path.display(), target.display());
+    Ok(())
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn short_hash(hash: &str) -> &str {
+    hash.get(..12).unwrap_or(hash)
+}
+
+/// Issues not yet repaired that were not in the previous report, or all of them
+/// when there is none
+fn count_new_issues(previous: Option<&ConsistencyReport>, report: &ConsistencyReport) -> usize {
+    let key = |issue: &ConsistencyIssue| (issue.kind, issue.document_id, issue.record_id, issue.path.clone());
+    let known: HashSet<_> = previous
+        .map(|previous| previous.issues.iter().filter(|issue| !issue.repaired).map(key).collect())
+        .unwrap_or_default();
+    report
+        .issues
+        .iter()
+        .filter(|issue| !issue.repaired && !known.contains(&key(issue)))
+        .count()
+}
+
+/// e.g. "2 missing files and 1 hash mismatch"
+fn summarize_issues(issues: &[ConsistencyIssue]) -> String {
+    let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for issue in issues.iter().filter(|issue| !issue.repaired) {
+        let names = match issue.kind {
+            ConsistencyIssueKind::MissingFile => ("missing file", "missing files"),
+            ConsistencyIssueKind::OrphanedFile => ("orphaned file", "orphaned files"),
+            ConsistencyIssueKind::HashMismatch => ("hash mismatch", "hash mismatches"),
+            ConsistencyIssueKind::DanglingGraphEdge => ("dangling graph edge", "dangling graph edges"),
+            ConsistencyIssueKind::OrphanedQueueEntry => ("orphaned queue entry", "orphaned queue entries"),
+        };
+        *counts.entry(names).or_default() += 1;
+    }
+    let parts: Vec<String> = counts
+        .into_iter()
+        .map(|((one, many), count)| format!("{} {}", count, if count == 1 { one } else { many }))
+        .collect();
+    match parts.split_last() {
+        None => "no issues".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// Stored files are named `<document id>.<ext>`, thumbnails `<document id>_thumb.jpg`
+fn document_id_from_file_name(name: &str) -> Option<Uuid> {
+    let stem = name.split('.').next()?;
+    let stem = stem.strip_suffix("_thumb").unwrap_or(stem);
+    Uuid::parse_str(stem).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_id_from_file_name() {
+        let id = Uuid::new_v4();
+        assert_eq!(document_id_from_file_name(&format!("{}.pdf", id)), Some(id));
+        assert_eq!(document_id_from_file_name(&format!("{}_thumb.jpg", id)), Some(id));
+        assert_eq!(document_id_from_file_name(&id.to_string()), Some(id));
+        assert_eq!(document_id_from_file_name("invoice.pdf"), None);
+    }
+
+    fn issue(kind: ConsistencyIssueKind, path: &str, repaired: bool) -> ConsistencyIssue {
+        ConsistencyIssue {
+            kind,
+            document_id: None,
+            record_id: None,
+            path: Some(path.to_string()),
+            detail: String::new(),
+            repaired,
+        }
+    }
+
+    fn report(issues: Vec<ConsistencyIssue>) -> ConsistencyReport {
+        ConsistencyReport {
+            started_at: Utc::now(),
+            finished_at: Utc::now(),
+            repair: false,
+            documents_checked: 0,
+            files_checked: 0,
+            hashes_checked: 0,
+            repaired_count: 0,
+            issues,
+        }
+    }
+
+    #[test]
+    fn test_only_new_issues_are_counted() {
+        let first = report(vec![issue(ConsistencyIssueKind::MissingFile, "a.pdf", false)]);
+        assert_eq!(count_new_issues(None, &first), 1);
+
+        let second = report(vec![
+            issue(ConsistencyIssueKind::MissingFile, "a.pdf", false),
+            issue(ConsistencyIssueKind::HashMismatch, "b.pdf", false),
+            issue(ConsistencyIssueKind::OrphanedFile, "c.pdf", true),
+        ]);
+        assert_eq!(count_new_issues(Some(&first), &second), 1);
+        assert_eq!(count_new_issues(Some(&second), &second), 0);
+    }
+
+    #[test]
+    fn test_summarize_issues() {
+        let issues = vec![
+            issue(ConsistencyIssueKind::MissingFile, "a.pdf", false),
+            issue(ConsistencyIssueKind::MissingFile, "b.pdf", false),
+            issue(ConsistencyIssueKind::HashMismatch, "c.pdf", false),
+            issue(ConsistencyIssueKind::OrphanedFile, "d.pdf", true),
+        ];
+        assert_eq!(summarize_issues(&issues), "1 hash mismatch and 2 missing files");
+        assert_eq!(summarize_issues(&issues[..1]), "1 missing file");
+        assert_eq!(summarize_issues(&[]), "no issues");
+    }
+
+    #[test]
+    fn test_mode_parsing() {
+        assert_eq!(ConsistencyCheckMode::parse("repair"), ConsistencyCheckMode::Repair);
+        assert_eq!(ConsistencyCheckMode::parse("Report"), ConsistencyCheckMode::Report);
+        assert_eq!(ConsistencyCheckMode::parse("off"), ConsistencyCheckMode::Off);
+    }
+}
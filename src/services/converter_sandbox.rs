@@ -0,0 +1,419 @@
+//! External format converters (LibreOffice, pdftoppm, pdftotext, antiword, ...) run in
+//! a sandbox, so one malicious or pathological file cannot take the ingestion pipeline
+//! down with it.
+//!
+//! Each converter runs as a separate process under `prlimit` with limits on CPU time,
+//! address space and the size of files it writes, and under `timeout`, which kills the
+//! whole process group, including helpers such as `soffice.bin`, once the wall clock
+//! limit passes. It gets an empty environment apart from `PATH`, the locale,
+//! `TESSDATA_PREFIX` and a home and temporary directory, so it cannot read the database
+//! URL or storage keys, and at most `CONVERTER_MAX_CONCURRENT` converters run at a time.
+//!
+//! A content a converter was killed on counts a strike. After
+//! `CONVERTER_QUARANTINE_STRIKES` strikes it is quarantined: no converter is started on
+//! it again until an admin releases it. Strikes are kept in the database; every node
+//! keeps a copy of the quarantined contents it syncs every few seconds.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output, Stdio};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+use crate::db::Database;
+
+/// How often strikes are saved and the quarantined contents reloaded
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+/// Seconds `timeout` waits after asking a converter to stop before killing it
+const KILL_AFTER_SECONDS: u64 = 5;
+/// Exit status of `timeout` when the converter ran out of time
+const TIMED_OUT_STATUS: i32 = 124;
+const SIGABRT: i32 = 6;
+const SIGBUS: i32 = 7;
+const SIGKILL: i32 = 9;
+const SIGSEGV: i32 = 11;
+const SIGXCPU: i32 = 24;
+const SIGXFSZ: i32 = 25;
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name).ok().and_then(|v| v.parse().ok()).filter(|v| *v > 0).unwrap_or(default)
+}
+
+/// Whether converters run under resource limits at all
+static ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("CONVERTER_SANDBOX_ENABLED")
+        .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+        .unwrap_or(true)
+});
+static CPU_SECONDS: Lazy<u64> = Lazy::new(|| env_u64("CONVERTER_CPU_SECONDS", 300));
+static MEMORY_MB: Lazy<u64> = Lazy::new(|| env_u64("CONVERTER_MEMORY_MB", 4096));
+static MAX_OUTPUT_MB: Lazy<u64> = Lazy::new(|| env_u64("CONVERTER_MAX_OUTPUT_MB", 1024));
+static QUARANTINE_STRIKES: Lazy<u32> = Lazy::new(|| env_u64("CONVERTER_QUARANTINE_STRIKES", 2).min(100) as u32);
+
+static SLOTS: Lazy<Semaphore> = Lazy::new(|| {
+    let default = std::thread::available_parallelism().map(|n| n.get() as u64).unwrap_or(2);
+    Semaphore::new(env_u64("CONVERTER_MAX_CONCURRENT", default) as usize)
+});
+
+/// Whether `prlimit` and `timeout` are installed; without them converters only get the
+/// wall clock limit
+static WRAPPERS_AVAILABLE: Lazy<bool> = Lazy::new(|| {
+    let available = ["prlimit", "timeout"].iter().all(|program| on_path(program));
+    if *ENABLED && !available {
+        warn!("prlimit or timeout is not installed; external converters run without CPU and memory limits");
+    }
+    available
+});
+
+static QUARANTINED: Lazy<RwLock<HashSet<String>>> = Lazy::new(|| RwLock::new(HashSet::new()));
+/// Strikes counted on this node since the last sync, per content
+static LOCAL_STRIKES: Lazy<Mutex<HashMap<String, u32>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// Strikes not saved yet
+static PENDING: Lazy<Mutex<Vec<Strike>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+struct Strike {
+    file_hash: String,
+    tool: String,
+    limit: ConverterLimit,
+}
+
+/// The limit a converter was killed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConverterLimit {
+    Time,
+    Cpu,
+    OutputSize,
+    /// Crashed or was killed, typically when it could not get more memory
+    Memory,
+}
+
+impl ConverterLimit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConverterLimit::Time => "time",
+            ConverterLimit::Cpu => "cpu",
+            ConverterLimit::OutputSize => "output_size",
+            ConverterLimit::Memory => "memory",
+        }
+    }
+}
+
+impl std::fmt::Display for ConverterLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            ConverterLimit::Time => "ran out of time",
+            ConverterLimit::Cpu => "exceeded its CPU time limit",
+            ConverterLimit::OutputSize => "exceeded its output size limit",
+            ConverterLimit::Memory => "crashed or ran out of memory",
+        };
+        f.write_str(description)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConverterError {
+    #[error("Failed to run {tool}: {source}")]
+    Spawn { tool: String, source: std::io::Error },
+    #[error("{tool} was killed: it {limit}")]
+    Killed { tool: String, limit: ConverterLimit },
+    #[error("{tool} was not started: the content {file_hash} is quarantined")]
+    Quarantined { tool: String, file_hash: String },
+}
+
+/// An external converter to run in the sandbox
+pub struct SandboxedCommand {
+    program: String,
+    args: Vec<OsString>,
+    current_dir: Option<PathBuf>,
+    input_hash: Option<String>,
+    timeout: Duration,
+}
+
+impl SandboxedCommand {
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            current_dir: None,
+            input_hash: None,
+            timeout: Duration::from_secs(env_u64("CONVERTER_TIMEOUT_SECONDS", 120)),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args.extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
+    /// Directory the converter runs in, and uses as its home and temporary directory
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// The content converted, so it is refused when quarantined and counted a strike
+    /// when the converter is killed
+    pub fn input(mut self, data: &[u8]) -> Self {
+        self.input_hash = Some(hash_data(data));
+        self
+    }
+
+    pub fn input_hash(mut self, file_hash: impl Into<String>) -> Self {
+        self.input_hash = Some(file_hash.into());
+        self
+    }
+
+    /// Wall clock limit, replacing `CONVERTER_TIMEOUT_SECONDS`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run the converter to completion and collect its output. A converter that fails
+    /// on its own returns its output with the failed status, like
+    /// `tokio::process::Command::output`.
+    pub async fn output(self) -> Result<Output, ConverterError> {
+        if let Some(file_hash) = &self.input_hash {
+            if is_quarantined(file_hash) {
+                return Err(ConverterError::Quarantined { tool: self.program, file_hash: file_hash.clone() });
+            }
+        }
+
+        let _slot = SLOTS.acquire().await.expect("converter slots are never closed");
+        let sandboxed = *ENABLED && *WRAPPERS_AVAILABLE;
+        let (program, args) = if sandboxed {
+            ("prlimit".to_string(), sandbox_args(&self.program, &self.args, self.timeout, &Limits::configured()))
+        } else {
+            (self.program.clone(), self.args.clone())
+        };
+
+        let scratch = self.current_dir.clone().unwrap_or_else(std::env::temp_dir);
+        let mut command = Command::new(&program);
+        command
+            .args(&args)
+            .env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_else(|| "/usr/local/bin:/usr/bin:/bin".into()))
+            .env("LANG", "C.UTF-8")
+            .env("HOME", &scratch)
+            .env("TMPDIR", &scratch)
+            .current_dir(&scratch)
+            .stdin(Stdio::null())
+            .process_group(0)
+            .kill_on_drop(true);
+        // Tesseract, also run by ocrmypdf, finds its language data through this
+        if let Some(tessdata) = std::env::var_os("TESSDATA_PREFIX") {
+            command.env("TESSDATA_PREFIX", tessdata);
+        }
+
+        // `timeout` stops the converter first; this only catches a stuck wrapper
+        let grace = Duration::from_secs(KILL_AFTER_SECONDS * 2);
+        let result = match tokio::time::timeout(self.timeout + grace, command.output()).await {
+            Ok(Ok(output)) => match exceeded_limit(output.status, sandboxed) {
+                Some(limit) => Err(limit),
+                None => Ok(output),
+            },
+            Ok(Err(source)) => return Err(ConverterError::Spawn { tool: self.program, source }),
+            Err(_) => Err(ConverterLimit::Time),
+        };
+
+        result.map_err(|limit| {
+            warn!("Killed {}: it {}", self.program, limit);
+            if let Some(file_hash) = self.input_hash {
+                strike(file_hash, &self.program, limit);
+            }
+            ConverterError::Killed { tool: self.program, limit }
+        })
+    }
+}
+
+struct Limits {
+    cpu_seconds: u64,
+    memory_bytes: u64,
+    output_bytes: u64,
+}
+
+impl Limits {
+    fn configured() -> Self {
+        Self {
+            cpu_seconds: *CPU_SECONDS,
+            memory_bytes: *MEMORY_MB * 1024 * 1024,
+            output_bytes: *MAX_OUTPUT_MB * 1024 * 1024,
+        }
+    }
+}
+
+/// Arguments of `prlimit` running the converter under `timeout`
+fn sandbox_args(program: &str, args: &[OsString], timeout: Duration, limits: &Limits) -> Vec<OsString> {
+    let mut wrapped: Vec<OsString> = vec![
+        format!("--cpu={}", limits.cpu_seconds).into(),
+        format!("--as={}", limits.memory_bytes).into(),
+        format!("--fsize={}", limits.output_bytes).into(),
+        "--core=0".into(),
+        "--".into(),
+        "timeout".into(),
+        format!("--kill-after={}", KILL_AFTER_SECONDS).into(),
+        timeout.as_secs().max(1).to_string().into(),
+        program.into(),
+    ];
+    wrapped.extend(args.iter().cloned());
+    wrapped
+}
+
+/// The limit a converter that ended with `status` was killed for, if it was
+fn exceeded_limit(status: ExitStatus, sandboxed: bool) -> Option<ConverterLimit> {
+    // `timeout` exits with 128 plus the signal when it cannot pass the signal on
+    let signal = status.signal().or_else(|| status.code().filter(|code| sandboxed && *code > 128).map(|code| code - 128));
+    if sandboxed && status.code() == Some(TIMED_OUT_STATUS) {
+        return Some(ConverterLimit::Time);
+    }
+    match signal? {
+        SIGXCPU => Some(ConverterLimit::Cpu),
+        SIGXFSZ => Some(ConverterLimit::OutputSize),
+        SIGKILL if sandboxed => Some(ConverterLimit::Time),
+        SIGKILL | SIGABRT | SIGBUS | SIGSEGV => Some(ConverterLimit::Memory),
+        _ => None,
+    }
+}
+
+pub fn hash_data(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// SHA-256 of a file, like `hash_data` of its content
+pub async fn hash_file(path: &Path) -> std::io::Result<String> {
+    Ok(hash_data(&tokio::fs::read(path).await?))
+}
+
+pub fn is_quarantined(file_hash: &str) -> bool {
+    QUARANTINED.read().unwrap_or_else(|e| e.into_inner()).contains(file_hash)
+}
+
+fn strike(file_hash: String, tool: &str, limit: ConverterLimit) {
+    let strikes = {
+        let mut local = LOCAL_STRIKES.lock().unwrap_or_else(|e| e.into_inner());
+        let strikes = local.entry(file_hash.clone()).or_insert(0);
+        *strikes += 1;
+        *strikes
+    };
+    if strikes >= *QUARANTINE_STRIKES {
+        warn!("Quarantined content {} after {} killed converter run(s)", file_hash, strikes);
+        QUARANTINED.write().unwrap_or_else(|e| e.into_inner()).insert(file_hash.clone());
+    }
+    PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Strike { file_hash, tool: tool.to_string(), limit });
+}
+
+/// Let converters run on a content again on this node; other nodes follow at their
+/// next sync
+pub fn release(file_hash: &str) {
+    QUARANTINED.write().unwrap_or_else(|e| e.into_inner()).remove(file_hash);
+    LOCAL_STRIKES.lock().unwrap_or_else(|e| e.into_inner()).remove(file_hash);
+}
+
+/// Save the strikes counted on this node and reload the quarantined contents
+pub async fn sync(db: &Database) {
+    let pending = std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()));
+    for strike in pending {
+        if let Err(e) = db
+            .record_converter_strike(&strike.file_hash, &strike.tool, strike.limit.as_str(), *QUARANTINE_STRIKES as i32)
+            .await
+        {
+            warn!("Failed to record killed {} on content {}: {}", strike.tool, strike.file_hash, e);
+            PENDING.lock().unwrap_or_else(|e| e.into_inner()).push(strike);
+        }
+    }
+    if !PENDING.lock().unwrap_or_else(|e| e.into_inner()).is_empty() {
+        return;
+    }
+
+    match db.get_quarantined_converter_hashes().await {
+        Ok(hashes) => {
+            LOCAL_STRIKES.lock().unwrap_or_else(|e| e.into_inner()).clear();
+            let mut quarantined = QUARANTINED.write().unwrap_or_else(|e| e.into_inner());
+            let hashes: HashSet<String> = hashes.into_iter().collect();
+            if hashes.len() != quarantined.len() {
+                info!("{} content(s) quarantined from external converters", hashes.len());
+            }
+            *quarantined = hashes;
+        }
+        Err(e) => warn!("Failed to load the contents quarantined from external converters: {}", e),
+    }
+}
+
+/// Keep this node's strikes saved and its quarantine current; runs on every node
+pub async fn run_sync(db: Database) {
+    let mut interval = tokio::time::interval(SYNC_INTERVAL);
+    loop {
+        interval.tick().await;
+        sync(&db).await;
+    }
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sandbox_args() {
+        let limits = Limits { cpu_seconds: 60, memory_bytes: 1 << 30, output_bytes: 1 << 20 };
+        let args = sandbox_args("pdftoppm", &["-png".into(), "in.pdf".into()], Duration::from_secs(30), &limits);
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string_lossy().to_string()).collect();
+        assert_eq!(
+            args,
+            [
+                "--cpu=60", "--as=1073741824", "--fsize=1048576", "--core=0", "--", "timeout", "--kill-after=5", "30",
+                "pdftoppm", "-png", "in.pdf",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exceeded_limit() {
+        assert_eq!(exceeded_limit(ExitStatus::from_raw(0), true), None);
+        // Exit code 1, an ordinary failure
+        assert_eq!(exceeded_limit(ExitStatus::from_raw(1 << 8), true), None);
+        assert_eq!(exceeded_limit(ExitStatus::from_raw(TIMED_OUT_STATUS << 8), true), Some(ConverterLimit::Time));
+        assert_eq!(exceeded_limit(ExitStatus::from_raw(TIMED_OUT_STATUS << 8), false), None);
+        assert_eq!(exceeded_limit(ExitStatus::from_raw(SIGXCPU), true), Some(ConverterLimit::Cpu));
+        assert_eq!(exceeded_limit(ExitStatus::from_raw((128 + SIGXFSZ) << 8), true), Some(ConverterLimit::OutputSize));
+        assert_eq!(exceeded_limit(ExitStatus::from_raw(SIGSEGV), false), Some(ConverterLimit::Memory));
+        assert_eq!(exceeded_limit(ExitStatus::from_raw(SIGKILL), true), Some(ConverterLimit::Time));
+    }
+
+    #[test]
+    fn test_strikes_quarantine() {
+        let file_hash = hash_data(b"pathological document");
+        assert!(!is_quarantined(&file_hash));
+        for _ in 0..*QUARANTINE_STRIKES {
+            strike(file_hash.clone(), "soffice", ConverterLimit::Time);
+        }
+        assert!(is_quarantined(&file_hash));
+        release(&file_hash);
+        assert!(!is_quarantined(&file_hash));
+    }
+}
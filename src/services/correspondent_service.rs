@@ -0,0 +1,204 @@
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{Correspondent, CreateCorrespondentRequest, Document};
+use crate::services::llm::llm_service::{LLMService, PromptTemplate};
+
+/// Whether to ask the LLM for the sender of documents no rule or name matched.
+/// Needs `LLM_API_KEY`.
+static LLM_EXTRACTION: Lazy<bool> = Lazy::new(|| {
+    std::env::var("CORRESPONDENT_LLM_EXTRACTION")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+});
+
+/// Characters of a document's text shown to the LLM; letterheads are at the top
+const LLM_TEXT_CHARS: usize = 3000;
+
+/// Longest correspondent name
+pub const MAX_NAME_LENGTH: usize = 200;
+
+/// Documents loaded per batch when matching existing documents
+const MATCH_BATCH_SIZE: i64 = 100;
+
+const LLM_SYSTEM_PROMPT: &str = "You identify who sent a document, such as the company on a letterhead or invoice, \
+    or the authority that issued it. Answer with JSON only: {\"correspondent\": \"<name>\"}, \
+    or {\"correspondent\": null} when the text does not show a sender.";
+const LLM_PROMPT: PromptTemplate = PromptTemplate::new("correspondent", 1);
+
+#[derive(Debug, Deserialize)]
+struct LlmCorrespondent {
+    correspondent: Option<String>,
+}
+
+/// Assigns correspondents to documents automatically: first by the match rules of
+/// the owner's correspondents, then by a correspondent's name occurring in the text,
+/// and finally, when enabled, by asking the LLM for the sender
+pub struct CorrespondentService {
+    db: Database,
+    llm_service: LLMService,
+}
+
+impl CorrespondentService {
+    pub fn new(db: Database) -> Self {
+        let llm_service = LLMService::new(db.get_pool().clone());
+        Self { db, llm_service }
+    }
+
+    /// Assign a correspondent to a freshly processed document that has none yet.
+    /// Returns the correspondent assigned.
+    pub async fn assign_automatically(&self, document: &Document) -> Result<Option<Correspondent>> {
+        if self.db.get_document_correspondent(document.id).await?.is_some() {
+            return Ok(None);
+        }
+        let Some(text) = document_text(document) else {
+            return Ok(None);
+        };
+
+        let correspondents = self.db.get_correspondents(document.user_id).await?;
+        let (correspondent, assigned_by) = match match_correspondent(&correspondents, text) {
+            Some(found) => (found.clone(), found_by(found)),
+            None if *LLM_EXTRACTION && self.llm_service.chat_enabled() => {
+                match self.extract_with_llm(document, text).await? {
+                    Some(correspondent) => (correspondent, "llm"),
+                    None => return Ok(None),
+                }
+            }
+            None => return Ok(None),
+        };
+
+        self.db
+            .assign_correspondent(document.user_id, &[document.id], Some(correspondent.id), assigned_by)
+            .await?;
+        info!(
+            "Assigned correspondent '{}' to document {} ({})",
+            correspondent.name, document.id, assigned_by
+        );
+        Ok(Some(correspondent))
+    }
+
+    /// Run the match rules and name detection over the user's documents that have no
+    /// correspondent, e.g. after adding correspondents. The LLM is not asked.
+    /// Returns the number of documents checked and assigned.
+    pub async fn match_existing(&self, user_id: Uuid) -> Result<(i64, i64)> {
+        let correspondents = self.db.get_correspondents(user_id).await?;
+        let mut checked = 0;
+        let mut assigned = 0;
+        let mut after = None;
+
+        loop {
+            let documents = self
+                .db
+                .get_documents_without_correspondent(user_id, after, MATCH_BATCH_SIZE)
+                .await?;
+            let Some(last) = documents.last() else {
+                break;
+            };
+            after = Some(last.id);
+
+            for document in &documents {
+                checked += 1;
+                let Some(text) = document_text(document) else {
+                    continue;
+                };
+                if let Some(found) = match_correspondent(&correspondents, text) {
+                    assigned += self
+                        .db
+                        .assign_correspondent(user_id, &[document.id], Some(found.id), found_by(found))
+                        .await?;
+                }
+            }
+        }
+
+        Ok((checked, assigned))
+    }
+
+    /// Ask the LLM for the sender and find or create a correspondent of that name
+    async fn extract_with_llm(&self, document: &Document, text: &str) -> Result<Option<Correspondent>> {
+        let excerpt: String = text.chars().take(LLM_TEXT_CHARS).collect();
+        let answer = match self.llm_service.complete(LLM_PROMPT, LLM_SYSTEM_PROMPT, &excerpt).await {
+            Ok(answer) => answer,
+            Err(e) => {
+                warn!("LLM correspondent extraction failed for document {}: {}", document.id, e);
+                return Ok(None);
+            }
+        };
+        let Some(name) = parse_llm_answer(&answer) else {
+            return Ok(None);
+        };
+
+        if let Some(existing) = self.db.get_correspondent_by_name(document.user_id, &name).await? {
+            return Ok(Some(existing));
+        }
+        let request = CreateCorrespondentRequest {
+            name,
+            match_algorithm: None,
+            match_pattern: None,
+            case_sensitive: None,
+        };
+        Ok(Some(self.db.create_correspondent(document.user_id, &request).await?))
+    }
+}
+
+/// OCR text, or the content of documents that have no OCR text
+fn document_text(document: &Document) -> Option<&str> {
+    let usable = |text: &&str| !text.trim().is_empty();
+    document
+        .ocr_text
+        .as_deref()
+        .filter(usable)
+        .or_else(|| document.content.as_deref().filter(usable))
+}
+
+/// The correspondent whose rule matches the text, otherwise the one with the longest
+/// name that occurs in it
+fn match_correspondent<'a>(correspondents: &'a [Correspondent], text: &str) -> Option<&'a Correspondent> {
+    correspondents
+        .iter()
+        .find(|correspondent| correspondent.rule_matches(text))
+        .or_else(|| {
+            correspondents
+                .iter()
+                .filter(|correspondent| correspondent.match_algorithm == "auto" && correspondent.name_occurs_in(text))
+                .max_by_key(|correspondent| correspondent.name.chars().count())
+        })
+}
+
+fn found_by(correspondent: &Correspondent) -> &'static str {
+    if correspondent.match_algorithm == "auto" {
+        "detected"
+    } else {
+        "rule"
+    }
+}
+
+/// The sender named in the LLM's answer, if it named one that is usable as a name
+fn parse_llm_answer(answer: &str) -> Option<String> {
+    let parsed: LlmCorrespondent = serde_json::from_str(answer).ok()?;
+    normalize_name(&parsed.correspondent?)
+}
+
+/// A trimmed correspondent name, or None when it is empty or too long
+pub fn normalize_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_llm_answer() {
+        assert_eq!(parse_llm_answer(r#"{"correspondent": " ACME Corp "}"#), Some("ACME Corp".to_string()));
+        assert_eq!(parse_llm_answer(r#"{"correspondent": null}"#), None);
+        assert_eq!(parse_llm_answer("ACME Corp"), None);
+    }
+}
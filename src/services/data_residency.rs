@@ -0,0 +1,93 @@
+//! Data residency: which external services may receive document content. Every
+//! request that sends content (document text, page images, files or their details)
+//! to the LLM, a translation or text-to-speech API, a search engine or an ingestion
+//! hook is checked against the instance's policy right before it is sent, and
+//! recorded in the audit log whether it went out or was blocked.
+//!
+//! The policy comes from `DATA_RESIDENCY_MODE` (`open`, `allowlist` or `offline`),
+//! `DATA_RESIDENCY_ALLOWED_HOSTS` and `DATA_RESIDENCY_ALLOWED_SERVICES`. Offline mode
+//! and modes that cannot be read keep all content in.
+
+use once_cell::sync::Lazy;
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::audit_log::{insert_audit_log_entry, NewAuditLogEntry};
+use crate::models::{url_host, ContentDestination, ContentDestinationStatus, DataResidencyMode, DataResidencyPolicy};
+use crate::services::audit_service;
+
+static POLICY: Lazy<DataResidencyPolicy> = Lazy::new(|| {
+    let policy = DataResidencyPolicy::from_lookup(|name| std::env::var(name).ok());
+    match policy.mode {
+        DataResidencyMode::Open => {}
+        DataResidencyMode::Allowlist => info!("Document content may only be sent to {:?}", policy.allowed_hosts),
+        DataResidencyMode::Offline => info!("Offline: no document content is sent to external services"),
+    }
+    policy
+});
+
+/// The policy the instance runs with
+pub fn policy() -> &'static DataResidencyPolicy {
+    &POLICY
+}
+
+/// Check that content about `documents` may be sent to the service at `url`, and
+/// record the transmission in the audit log whether it is let through or not. Call
+/// right before sending; the error is why the content was kept in.
+pub async fn authorize(
+    pool: &PgPool,
+    destination: ContentDestination,
+    url: &str,
+    documents: &[Uuid],
+    bytes: usize,
+) -> Result<(), String> {
+    let decision = POLICY.check(destination, url);
+    let action = match decision {
+        Ok(()) => audit_service::CONTENT_TRANSMIT,
+        Err(_) => audit_service::CONTENT_TRANSMIT_BLOCKED,
+    };
+
+    let mut details = json!({
+        "destination": destination.as_str(),
+        "host": url_host(url),
+        "bytes": bytes,
+    });
+    if documents.len() > 1 {
+        details["document_ids"] = json!(documents);
+    }
+    if let Err(reason) = &decision {
+        details["reason"] = json!(reason);
+    }
+    let entry = NewAuditLogEntry {
+        action: action.to_string(),
+        resource_type: "document".to_string(),
+        resource_id: match documents {
+            [document_id] => Some(*document_id),
+            _ => None,
+        },
+        details: Some(details),
+        ..Default::default()
+    };
+    if let Err(e) = insert_audit_log_entry(pool, &entry).await {
+        warn!("Failed to write {} audit log entry: {}", entry.action, e);
+    }
+
+    decision.map_err(|reason| {
+        warn!("Kept content for {} in: {}", destination.as_str(), reason);
+        format!("Data residency policy does not allow sending content to {}: {}", destination.as_str(), reason)
+    })
+}
+
+/// Whether the service configured at `url` may receive content
+pub fn destination_status(destination: ContentDestination, name: Option<&str>, url: &str) -> ContentDestinationStatus {
+    let decision = POLICY.check(destination, url);
+    ContentDestinationStatus {
+        destination,
+        name: name.map(str::to_string),
+        host: url_host(url),
+        allowed: decision.is_ok(),
+        reason: decision.err(),
+    }
+}
@@ -0,0 +1,333 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{Digest, DigestPreview, DigestSettings, NotificationChannelConfig};
+use crate::services::i18n;
+use crate::services::notification_service::NotificationService;
+
+/// How often due digests are looked for
+const POLL_SECONDS: u64 = 300;
+/// Documents and failures listed by name; the counts cover the rest
+const LIST_LIMIT: i64 = 20;
+
+/// Subject and body templates that replace the `digest.subject` and `digest.body`
+/// entries of the user's translation catalog. `{{name}}` placeholders are replaced by
+/// the digest's values; unknown ones are left as they are.
+#[derive(Debug, Clone, Default)]
+struct DigestTemplates {
+    subject: Option<String>,
+    body: Option<String>,
+}
+
+impl DigestTemplates {
+    /// `DIGEST_SUBJECT_TEMPLATE` and the file `DIGEST_BODY_TEMPLATE_FILE` replace the
+    /// catalog's templates for every locale
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let body = var("DIGEST_BODY_TEMPLATE_FILE").and_then(|path| match std::fs::read_to_string(&path) {
+            Ok(body) => Some(body),
+            Err(e) => {
+                warn!("Failed to read digest template {}, using the catalog's one: {}", path, e);
+                None
+            }
+        });
+
+        Self {
+            subject: var("DIGEST_SUBJECT_TEMPLATE"),
+            body,
+        }
+    }
+}
+
+/// One line per entry, and how many more there are when the list was cut short
+fn render_list(locale: &str, lines: Vec<String>, total: i64) -> String {
+    if lines.is_empty() {
+        return format!("  {}", i18n::translate(locale, "digest.list.none", &[]));
+    }
+    let mut rendered: Vec<String> = lines.iter().map(|line| format!("  - {}", line)).collect();
+    let more = total - lines.len() as i64;
+    if more > 0 {
+        rendered.push(format!("  {}", i18n::translate(locale, "digest.list.more", &[("count", more.to_string())])));
+    }
+    rendered.join("\n")
+}
+
+/// Subject and body of a digest in the given locale
+fn render_digest(
+    templates: &DigestTemplates,
+    locale: &str,
+    digest: &Digest,
+    settings: &DigestSettings,
+    username: &str,
+    public_url: Option<&str>,
+) -> (String, String) {
+    let date = |at: DateTime<Utc>| at.format("%Y-%m-%d %H:%M UTC").to_string();
+    let new_documents = render_list(
+        locale,
+        digest
+            .new_documents
+            .iter()
+            .map(|document| match public_url {
+                Some(url) => format!("{} ({}/documents/{})", document.original_filename, url, document.id),
+                None => document.original_filename.clone(),
+            })
+            .collect(),
+        digest.new_document_count,
+    );
+    let failures = render_list(
+        locale,
+        digest
+            .failures
+            .iter()
+            .map(|failure| {
+                i18n::translate(
+                    locale,
+                    "digest.failure",
+                    &[
+                        ("filename", failure.filename.clone()),
+                        ("reason", failure.failure_reason.clone()),
+                        ("stage", failure.failure_stage.clone()),
+                    ],
+                )
+            })
+            .collect(),
+        digest.failure_count,
+    );
+    let match_count: i64 = digest.search_matches.iter().map(|m| m.matches).sum();
+    let search_matches = render_list(
+        locale,
+        digest
+            .search_matches
+            .iter()
+            .map(|m| {
+                i18n::translate(
+                    locale,
+                    "digest.search_match",
+                    &[("name", m.name.clone()), ("count", m.matches.to_string())],
+                )
+            })
+            .collect(),
+        digest.search_matches.len() as i64,
+    );
+    let link = public_url.map(|url| format!("\n{}/documents", url)).unwrap_or_default();
+    let frequency = i18n::translate(locale, &format!("digest.frequency.{}", settings.frequency), &[]);
+
+    let values = [
+        ("username", username.to_string()),
+        ("frequency", frequency),
+        ("period_start", date(digest.period_start)),
+        ("period_end", date(digest.period_end)),
+        ("new_document_count", digest.new_document_count.to_string()),
+        ("new_documents", new_documents),
+        ("failure_count", digest.failure_count.to_string()),
+        ("failures", failures),
+        ("match_count", match_count.to_string()),
+        ("search_matches", search_matches),
+        ("link", link),
+    ];
+    let render = |template: &Option<String>, key: &str| match template {
+        Some(template) => i18n::render_template(template, &values),
+        None => i18n::translate(locale, key, &values),
+    };
+    (render(&templates.subject, "digest.subject"), render(&templates.body, "digest.body"))
+}
+
+/// Scheduled job that emails users a daily or weekly summary of new documents, failed
+/// jobs and saved search matches. Digests go to the user's enabled email notification
+/// channels, or their account's address when they have none. A digest that cannot be
+/// sent is not retried; its error is kept in the settings and the next digest starts
+/// where it ended.
+pub struct DigestService {
+    db: Database,
+    notifications: NotificationService,
+    templates: DigestTemplates,
+}
+
+impl DigestService {
+    pub fn new(db: Database) -> Self {
+        Self {
+            notifications: NotificationService::new(db.clone()),
+            db,
+            templates: DigestTemplates::from_env(),
+        }
+    }
+
+    /// Send due digests every 5 minutes
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(POLL_SECONDS));
+        loop {
+            interval.tick().await;
+            match self.run_once().await {
+                Ok(sent) if sent > 0 => info!("Sent {} digest(s)", sent),
+                Ok(_) => {}
+                Err(e) => error!("Failed to send digests: {}", e),
+            }
+        }
+    }
+
+    /// Send the digests that are due; returns how many were sent
+    pub async fn run_once(&self) -> Result<usize> {
+        let now = Utc::now();
+        let mut sent = 0;
+        for settings in self.db.get_active_digest_settings().await? {
+            if !settings.is_due(now) {
+                continue;
+            }
+            if !self.db.claim_digest(settings.user_id, settings.last_sent_at, now).await? {
+                continue;
+            }
+
+            let error = match self.send(&settings, now).await {
+                Ok(false) => continue,
+                Ok(true) => {
+                    sent += 1;
+                    None
+                }
+                Err(e) => {
+                    warn!("Failed to send the digest of user {}: {}", settings.user_id, e);
+                    Some(e.to_string())
+                }
+            };
+            self.db.record_digest_error(settings.user_id, error.as_deref()).await?;
+        }
+        Ok(sent)
+    }
+
+    /// Send the digest of the period ending `now`. Returns false when it was empty and
+    /// the user skips empty digests.
+    async fn send(&self, settings: &DigestSettings, now: DateTime<Utc>) -> Result<bool> {
+        let preview = self.preview(settings, now).await?;
+        if settings.skip_empty && preview.digest.is_empty() {
+            return Ok(false);
+        }
+        self.deliver(settings.user_id, &preview.subject, &preview.body).await?;
+        Ok(true)
+    }
+
+    /// What the user's next digest would say if it went out at `now`
+    pub async fn preview(&self, settings: &DigestSettings, now: DateTime<Utc>) -> Result<DigestPreview> {
+        let user = self
+            .db
+            .get_user_by_id(settings.user_id)
+            .await?
+            .ok_or_else(|| anyhow!("User {} no longer exists", settings.user_id))?;
+        let digest = self.build(settings.user_id, settings.period_start(now), now).await?;
+        let locale = i18n::locale_of(&self.db, settings.user_id).await;
+        let (subject, body) = render_digest(
+            &self.templates,
+            &locale,
+            &digest,
+            settings,
+            &user.username,
+            self.notifications.public_url(),
+        );
+
+        Ok(DigestPreview { subject, body, digest })
+    }
+
+    async fn build(&self, user_id: Uuid, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Digest> {
+        let (new_documents, new_document_count) = self.db.get_digest_documents(user_id, since, until, LIST_LIMIT).await?;
+        let (failures, failure_count) = self.db.get_digest_failures(user_id, since, until, LIST_LIMIT).await?;
+        let search_matches = self.db.get_digest_search_matches(user_id, since, until).await?;
+
+        Ok(Digest {
+            user_id,
+            period_start: since,
+            period_end: until,
+            new_document_count,
+            new_documents,
+            failure_count,
+            failures,
+            search_matches,
+        })
+    }
+
+    /// Email a digest to the user's enabled email channels, or their own address. Fails
+    /// only when no channel accepted it.
+    pub async fn deliver(&self, user_id: Uuid, subject: &str, body: &str) -> Result<()> {
+        let channels: Vec<_> = self
+            .db
+            .get_enabled_notification_channels(user_id)
+            .await?
+            .into_iter()
+            .filter(|channel| matches!(channel.config.0, NotificationChannelConfig::Email { .. }))
+            .collect();
+        if channels.is_empty() {
+            return self.notifications.send_email_message(user_id, None, subject, body.to_string()).await;
+        }
+
+        let mut last_error = None;
+        let mut delivered = false;
+        for channel in &channels {
+            let NotificationChannelConfig::Email { to } = &channel.config.0 else {
+                continue;
+            };
+            let result = self
+                .notifications
+                .send_email_message(user_id, to.as_deref(), subject, body.to_string())
+                .await;
+            let error = result.as_ref().err().map(|e| e.to_string());
+            if let Err(e) = self.db.record_notification_channel_send(channel.id, error.as_deref()).await {
+                warn!("Failed to record send to notification channel {}: {}", channel.id, e);
+            }
+            match result {
+                Ok(()) => delivered = true,
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        match (delivered, last_error) {
+            (false, Some(e)) => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DigestDocument, DigestFrequency, DigestSearchMatch};
+
+    #[test]
+    fn test_render_list() {
+        assert_eq!(render_list("en", vec![], 0), "  none");
+        assert_eq!(render_list("en", vec!["a.pdf".to_string()], 3), "  - a.pdf\n  ... and 2 more");
+    }
+
+    #[test]
+    fn test_render_digest() {
+        let now = Utc::now();
+        let settings = DigestSettings { frequency: DigestFrequency::Daily, ..DigestSettings::default_for(Uuid::new_v4(), now) };
+        let document_id = Uuid::new_v4();
+        let digest = Digest {
+            user_id: settings.user_id,
+            period_start: now - chrono::Duration::days(1),
+            period_end: now,
+            new_document_count: 1,
+            new_documents: vec![DigestDocument { id: document_id, original_filename: "lease.pdf".to_string(), created_at: now }],
+            failure_count: 0,
+            failures: vec![],
+            search_matches: vec![DigestSearchMatch { saved_search_id: Uuid::new_v4(), name: "Leases".to_string(), matches: 2 }],
+        };
+        let templates = DigestTemplates::default();
+
+        let (subject, body) =
+            render_digest(&templates, "en", &digest, &settings, "ada", Some("https://docs.example.com"));
+        assert_eq!(subject, "Readur daily digest: 1 new document(s)");
+        assert!(body.starts_with("Hello ada,"));
+        assert!(body.contains(&format!("lease.pdf (https://docs.example.com/documents/{})", document_id)));
+        assert!(body.contains("Saved search matches: 2\n  - Leases: 2 new match(es)"));
+        assert!(!body.contains("{{"));
+
+        let (subject, _) = render_digest(&templates, "de", &digest, &settings, "ada", None);
+        assert!(!subject.contains("daily"));
+
+        let templates = DigestTemplates { subject: Some("{{new_document_count}} for {{username}}".to_string()), body: None };
+        let (subject, _) = render_digest(&templates, "de", &digest, &settings, "ada", None);
+        assert_eq!(subject, "1 for ada");
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::{DiffLineKind, Document, DocumentCompareResponse, PageDifference};
+use crate::services::document_version_service::diff_lines;
+use crate::services::file_service::FileService;
+
+/// Pages beyond this are not compared
+const DEFAULT_MAX_PAGES: usize = 20;
+/// Resolution pages are rendered at for comparing; enough to tell lines of text apart
+#[cfg(feature = "ocr")]
+const RENDER_DPI: u32 = 50;
+
+/// Compares two documents, such as two revisions of a contract or two scans of the
+/// same paper: their OCR texts line by line and their pages by how they look
+pub struct DocumentCompareService {
+    file_service: FileService,
+    max_pages: usize,
+}
+
+impl DocumentCompareService {
+    pub fn new(file_service: FileService) -> Self {
+        let max_pages = std::env::var("DOCUMENT_COMPARE_MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_PAGES)
+            .max(1);
+
+        Self { file_service, max_pages }
+    }
+
+    /// Render the first pages of both documents and score how different each pair of
+    /// pages looks
+    #[cfg(feature = "ocr")]
+    pub async fn compare_pages(&self, a: &Document, b: &Document) -> Result<Vec<PageDifference>> {
+        use crate::ocr::{page_images, perceptual_hash};
+
+        let a_data = self.file_service.read_file(&a.file_path).await?;
+        let a_pages = page_images::render_pages(&a_data, &a.mime_type, RENDER_DPI, self.max_pages).await?;
+        drop(a_data);
+        let b_data = self.file_service.read_file(&b.file_path).await?;
+        let b_pages = page_images::render_pages(&b_data, &b.mime_type, RENDER_DPI, self.max_pages).await?;
+
+        let scores: Vec<f64> = a_pages
+            .iter()
+            .zip(&b_pages)
+            .map(|(a, b)| perceptual_hash::image_difference(a, b))
+            .collect();
+        Ok(page_differences(&scores, a_pages.len(), b_pages.len()))
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub async fn compare_pages(&self, _a: &Document, _b: &Document) -> Result<Vec<PageDifference>> {
+        anyhow::bail!("Page comparison requires OCR feature")
+    }
+}
+
+/// Line diff of two OCR texts, without page scores
+pub fn compare_texts(a: Uuid, b: Uuid, a_text: &str, b_text: &str) -> DocumentCompareResponse {
+    let lines = diff_lines(a_text, b_text);
+    let count = |kind: DiffLineKind| lines.iter().filter(|line| line.kind == kind).count() as i64;
+    let (lines_added, lines_removed, unchanged) =
+        (count(DiffLineKind::Added), count(DiffLineKind::Removed), count(DiffLineKind::Unchanged));
+
+    // Each unchanged line is in both texts
+    let total = 2 * unchanged + lines_added + lines_removed;
+    let text_similarity = if total == 0 { 1.0 } else { (2 * unchanged) as f64 / total as f64 };
+
+    DocumentCompareResponse {
+        a,
+        b,
+        lines,
+        lines_added,
+        lines_removed,
+        text_similarity,
+        pages_compared: false,
+        pages: Vec::new(),
+    }
+}
+
+/// One entry per page of the longer document, given the scores of the pages both have
+pub fn page_differences(scores: &[f64], a_pages: usize, b_pages: usize) -> Vec<PageDifference> {
+    (0..a_pages.max(b_pages))
+        .map(|index| PageDifference {
+            page: index as u32 + 1,
+            score: scores.get(index).copied().unwrap_or(1.0),
+            in_a: index < a_pages,
+            in_b: index < b_pages,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_texts() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let comparison = compare_texts(a, b, "Parties\nTerm: 12 months\nSigned", "Parties\nTerm: 24 months\nSigned");
+        assert_eq!(comparison.lines_added, 1);
+        assert_eq!(comparison.lines_removed, 1);
+        assert!((comparison.text_similarity - 4.0 / 6.0).abs() < 1e-9);
+        assert!(!comparison.pages_compared);
+
+        assert_eq!(compare_texts(a, b, "", "").text_similarity, 1.0);
+        assert_eq!(compare_texts(a, b, "one", "two").text_similarity, 0.0);
+    }
+
+    #[test]
+    fn test_page_differences() {
+        let pages = page_differences(&[0.0, 0.25], 3, 2);
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[1], PageDifference { page: 2, score: 0.25, in_a: true, in_b: true });
+        assert_eq!(pages[2], PageDifference { page: 3, score: 1.0, in_a: true, in_b: false });
+        assert!(page_differences(&[], 0, 0).is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::ingestion::document_ingestion::{
+    DeduplicationPolicy, DocumentIngestionRequest, DocumentIngestionService, IngestionResult,
+};
+use crate::models::{Document, PageTransform};
+use crate::ocr::pdf_pages::{self, TempPdf};
+use crate::services::file_service::FileService;
+use crate::services::legal_hold_service;
+use crate::services::thumbnail_service::requeue_thumbnail;
+
+/// Page-level editing of stored PDFs: merging several scans into one document
+/// and fixing the page order of a document in place
+pub struct DocumentPagesService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl DocumentPagesService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Concatenate PDF documents, in the given order, into a new document owned by `user_id`
+    pub async fn merge(&self, user_id: Uuid, documents: &[Document], filename: Option<&str>) -> Result<Document> {
+        if documents.len() < 2 {
+            return Err(anyhow!("At least two documents are required to merge"));
+        }
+        if let Some(document) = documents.iter().find(|d| d.mime_type != "application/pdf") {
+            return Err(anyhow!("Document {} is not a PDF", document.id));
+        }
+
+        let mut parts = Vec::with_capacity(documents.len());
+        for document in documents {
+            parts.push(self.file_service.read_file(&document.file_path).await?);
+        }
+        let merged = pdf_pages::concatenate(&parts).await?;
+
+        let filename = match filename.map(str::trim).filter(|f| !f.is_empty()) {
+            Some(name) if name.to_lowercase().ends_with(".pdf") => name.to_string(),
+            Some(name) => format!("{}.pdf", name),
+            None => format!("{}_merged.pdf", file_stem(&documents[0].original_filename)),
+        };
+
+        let request = DocumentIngestionRequest {
+            filename: filename.clone(),
+            original_filename: filename,
+            file_data: merged,
+            mime_type: "application/pdf".to_string(),
+            user_id,
+            deduplication_policy: DeduplicationPolicy::AllowDuplicateContent,
+            source_type: Some("merge".to_string()),
+            source_id: None,
+            original_created_at: None,
+            original_modified_at: None,
+            source_path: None,
+            file_permissions: None,
+            file_owner: None,
+            file_group: None,
+            source_metadata: Some(serde_json::json!({
+                "merged_from": documents.iter().map(|d| d.id).collect::<Vec<_>>(),
+            })),
+        };
+
+        let ingestion_service = DocumentIngestionService::new(self.db.clone(), self.file_service.clone());
+        match ingestion_service.ingest_document(request).await {
+            Ok(IngestionResult::Created(document)) | Ok(IngestionResult::ExistingDocument(document)) => {
+                info!("Merged {} documents into {}", documents.len(), document.id);
+                Ok(document)
+            }
+            Ok(other) => Err(anyhow!("Merged document was not stored: {:?}", other)),
+            Err(e) => Err(anyhow!("Failed to store merged document: {}", e)),
+        }
+    }
+
+    pub async fn page_count(&self, document: &Document) -> Result<u32> {
+        let data = self.file_service.read_file(&document.file_path).await?;
+        TempPdf::from_bytes(&data).await?.page_count().await
+    }
+
+    /// Rewrite a PDF with its pages in `page_order` (1-based, a permutation of all pages).
+    /// The document's OCR state is reset; the caller queues it for OCR again.
+    pub async fn reorder(&self, document: &Document, page_order: &[u32]) -> Result<Document> {
+        if document.mime_type != "application/pdf" {
+            return Err(anyhow!("Only PDF pages can be reordered"));
+        }
+        legal_hold_service::ensure_not_held(&self.db, document.id).await?;
+
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let pdf = TempPdf::from_bytes(&data).await?;
+        let page_count = pdf.page_count().await?;
+        validate_page_order(page_order, page_count).map_err(|e| anyhow!(e))?;
+
+        let reordered = pdf.extract_pages(page_order).await?;
+        let transforms = self.db.get_page_transforms(document.id).await?;
+        let file_hash = format!("{:x}", Sha256::digest(&reordered));
+
+        let file_path = self
+            .file_service
+            .store_document_content(document.user_id, document.id, &document.filename, &reordered, &file_hash)
+            .await?;
+        self.file_service.invalidate_thumbnail(&document.file_path).await;
+        self.file_service.invalidate_thumbnail(&file_path).await;
+
+        let updated = self
+            .db
+            .replace_document_file(document.id, &file_path, reordered.len() as i64, &file_hash)
+            .await?;
+        if let Err(e) = self.file_service.release_blob(&document.file_path).await {
+            warn!("Failed to release blob {} of document {}: {}", document.file_path, document.id, e);
+        }
+        // Page transforms move with their pages
+        let moved = reorder_transforms(&transforms, page_order);
+        if let Err(e) = self.db.replace_page_transforms(document.id, &moved).await {
+            warn!("Failed to move the page transforms of document {}: {}", document.id, e);
+        }
+
+        requeue_thumbnail(&self.db, document.id).await;
+        info!("Reordered {} pages of document {}", page_count, document.id);
+        Ok(updated)
+    }
+}
+
+/// A valid order lists every page exactly once
+pub fn validate_page_order(page_order: &[u32], page_count: u32) -> Result<(), String> {
+    if page_order.len() != page_count as usize {
+        return Err(format!("Expected {} pages, got {}", page_count, page_order.len()));
+    }
+
+    let mut seen = vec![false; page_count as usize];
+    for &page in page_order {
+        if page == 0 || page > page_count {
+            return Err(format!("Page {} does not exist", page));
+        }
+        if std::mem::replace(&mut seen[page as usize - 1], true) {
+            return Err(format!("Page {} is listed more than once", page));
+        }
+    }
+
+    Ok(())
+}
+
+/// Transforms renumbered for pages in `page_order`: new page `i` is old page `page_order[i - 1]`
+fn reorder_transforms(transforms: &[PageTransform], page_order: &[u32]) -> Vec<PageTransform> {
+    page_order
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &old)| {
+            let transform = transforms.iter().find(|t| t.page_number == old as i32)?;
+            Some(PageTransform { page_number: i as i32 + 1, ..transform.clone() })
+        })
+        .collect()
+}
+
+fn file_stem(filename: &str) -> String {
+    Path::new(filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "document".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_page_order() {
+        assert!(validate_page_order(&[3, 1, 2], 3).is_ok());
+        assert!(validate_page_order(&[1, 2], 3).is_err());
+        assert!(validate_page_order(&[1, 1, 2], 3).is_err());
+        assert!(validate_page_order(&[0, 1, 2], 3).is_err());
+        assert!(validate_page_order(&[1, 2, 4], 3).is_err());
+    }
+
+    #[test]
+    fn test_reorder_transforms() {
+        let document_id = Uuid::new_v4();
+        let turned = PageTransform { rotation: 90, ..PageTransform::identity(document_id, 1) };
+
+        let moved = reorder_transforms(&[turned], &[2, 3, 1]);
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].page_number, 3);
+        assert_eq!(moved[0].rotation, 90);
+    }
+}
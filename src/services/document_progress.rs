@@ -0,0 +1,256 @@
+//! Progress of documents through ingestion, OCR and the analysis steps after it, published as
+//! transient `document.progress` events for the per-document event stream.
+//!
+//! Progress is kept in memory while a document is processed in this process. OCR
+//! reports when it starts and ends; analysis reports each finished step of those
+//! started for the document. Once a document is done its entry is dropped, and its
+//! progress is read from the document itself.
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::errors::panic::spawn_guarded;
+use crate::models::{Document, DocumentProgress, EventType, ProgressStage, ProgressStatus};
+use crate::services::event_service;
+
+/// Share of the overall progress each stage ends at
+const INGESTION_DONE_PERCENT: u8 = 10;
+const OCR_DONE_PERCENT: u8 = 70;
+
+struct Tracked {
+    user_id: Option<Uuid>,
+    progress: DocumentProgress,
+    /// Analysis steps started and finished
+    steps_started: u32,
+    steps_finished: u32,
+    /// While the steps are still being started, finishing them all does not end analysis
+    planning: bool,
+}
+
+static PROGRESS: Lazy<Mutex<HashMap<Uuid, Tracked>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Overall percent of a document at `percent` through `stage`
+pub fn overall_percent(stage: ProgressStage, status: ProgressStatus, percent: u8) -> u8 {
+    let (start, end) = match stage {
+        ProgressStage::Ingestion => (0, INGESTION_DONE_PERCENT),
+        ProgressStage::Ocr => (INGESTION_DONE_PERCENT, OCR_DONE_PERCENT),
+        ProgressStage::Analysis => (OCR_DONE_PERCENT, 100),
+    };
+    if status == ProgressStatus::Completed {
+        return end;
+    }
+    start + ((end - start) as u32 * percent.min(100) as u32 / 100) as u8
+}
+
+fn progress(document_id: Uuid, stage: ProgressStage, status: ProgressStatus, percent: u8) -> DocumentProgress {
+    DocumentProgress {
+        document_id,
+        stage,
+        status,
+        percent,
+        overall_percent: overall_percent(stage, status, percent),
+        updated_at: Utc::now(),
+    }
+}
+
+fn publish(user_id: Option<Uuid>, progress: &DocumentProgress) {
+    event_service::broadcast_event(
+        EventType::DocumentProgress,
+        user_id,
+        Some(progress.document_id),
+        serde_json::json!(progress),
+    );
+}
+
+/// Record and publish a document's progress; `done` drops its entry afterwards
+fn update(user_id: Option<Uuid>, progress: DocumentProgress, done: bool) {
+    {
+        let mut tracked = PROGRESS.lock().unwrap();
+        if done {
+            tracked.remove(&progress.document_id);
+        } else {
+            let entry = tracked.entry(progress.document_id).or_insert_with(|| Tracked {
+                user_id,
+                progress: progress.clone(),
+                steps_started: 0,
+                steps_finished: 0,
+                planning: false,
+            });
+            entry.progress = progress.clone();
+        }
+    }
+    publish(user_id, &progress);
+}
+
+/// The progress of a document being processed in this process
+pub fn current(document_id: Uuid) -> Option<DocumentProgress> {
+    PROGRESS.lock().unwrap().get(&document_id).map(|tracked| tracked.progress.clone())
+}
+
+/// Progress of a document that is not being processed here, from its OCR status
+pub fn from_document(document: &Document) -> DocumentProgress {
+    let (stage, status, percent) = match document.ocr_status.as_deref() {
+        Some("processing") => (ProgressStage::Ocr, ProgressStatus::Running, 0),
+        Some("completed") => (ProgressStage::Analysis, ProgressStatus::Completed, 100),
+        Some("failed") => (ProgressStage::Ocr, ProgressStatus::Failed, 0),
+        _ => (ProgressStage::Ocr, ProgressStatus::Queued, 0),
+    };
+    DocumentProgress {
+        updated_at: document.updated_at,
+        ..progress(document.id, stage, status, percent)
+    }
+}
+
+/// A document was stored and recorded; OCR comes next
+pub fn ingested(user_id: Uuid, document_id: Uuid) {
+    publish(Some(user_id), &progress(document_id, ProgressStage::Ingestion, ProgressStatus::Completed, 100));
+}
+
+pub fn ocr_started(user_id: Option<Uuid>, document_id: Uuid) {
+    update(user_id, progress(document_id, ProgressStage::Ocr, ProgressStatus::Running, 0), false);
+}
+
+/// OCR failed; nothing runs after it
+pub fn ocr_failed(user_id: Option<Uuid>, document_id: Uuid) {
+    update(user_id, progress(document_id, ProgressStage::Ocr, ProgressStatus::Failed, 0), true);
+}
+
+/// OCR completed and the analysis steps are about to be started with
+/// `spawn_analysis_step`. Analysis ends when the returned plan is dropped and every
+/// step started until then has finished.
+pub fn begin_analysis(user_id: Option<Uuid>, document_id: Uuid) -> AnalysisPlan {
+    publish(user_id, &progress(document_id, ProgressStage::Ocr, ProgressStatus::Completed, 100));
+    let analysis = progress(document_id, ProgressStage::Analysis, ProgressStatus::Running, 0);
+    PROGRESS.lock().unwrap().insert(
+        document_id,
+        Tracked {
+            user_id,
+            progress: analysis.clone(),
+            steps_started: 0,
+            steps_finished: 0,
+            planning: true,
+        },
+    );
+    publish(user_id, &analysis);
+    AnalysisPlan { document_id }
+}
+
+/// Ends the starting of a document's analysis steps when dropped
+pub struct AnalysisPlan {
+    document_id: Uuid,
+}
+
+impl Drop for AnalysisPlan {
+    fn drop(&mut self) {
+        step_changed(self.document_id, |tracked| tracked.planning = false);
+    }
+}
+
+/// Counts an analysis step finished when dropped, also when the step panicked
+struct AnalysisStep {
+    document_id: Uuid,
+}
+
+impl Drop for AnalysisStep {
+    fn drop(&mut self) {
+        step_changed(self.document_id, |tracked| tracked.steps_finished += 1);
+    }
+}
+
+/// Apply a change to a document's analysis steps and publish the progress it makes
+fn step_changed(document_id: Uuid, change: impl FnOnce(&mut Tracked)) {
+    let (user_id, changed) = {
+        let mut tracked = PROGRESS.lock().unwrap();
+        let Some(entry) = tracked.get_mut(&document_id) else {
+            return;
+        };
+        change(entry);
+        if entry.planning {
+            return;
+        }
+
+        let done = entry.steps_finished >= entry.steps_started;
+        let (status, percent) = if done {
+            (ProgressStatus::Completed, 100)
+        } else {
+            (ProgressStatus::Running, (entry.steps_finished * 100 / entry.steps_started) as u8)
+        };
+        entry.progress = progress(document_id, ProgressStage::Analysis, status, percent);
+        let changed = (entry.user_id, entry.progress.clone());
+        if done {
+            tracked.remove(&document_id);
+        }
+        changed
+    };
+    publish(user_id, &changed);
+}
+
+/// Spawn an analysis step of a document, counted in its progress and in its processing
+/// usage
+pub fn spawn_analysis_step<F>(document_id: Uuid, context: impl Into<String>, future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    if let Some(tracked) = PROGRESS.lock().unwrap().get_mut(&document_id) {
+        tracked.steps_started += 1;
+    }
+    let step = AnalysisStep { document_id };
+    spawn_guarded(context, async move {
+        let _step = step;
+        super::processing_usage::for_document(document_id, future).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overall_percent_spans_the_stages() {
+        assert_eq!(overall_percent(ProgressStage::Ingestion, ProgressStatus::Completed, 100), 10);
+        assert_eq!(overall_percent(ProgressStage::Ocr, ProgressStatus::Running, 0), 10);
+        assert_eq!(overall_percent(ProgressStage::Ocr, ProgressStatus::Running, 50), 40);
+        assert_eq!(overall_percent(ProgressStage::Ocr, ProgressStatus::Completed, 0), 70);
+        assert_eq!(overall_percent(ProgressStage::Analysis, ProgressStatus::Running, 50), 85);
+        assert_eq!(overall_percent(ProgressStage::Analysis, ProgressStatus::Completed, 100), 100);
+    }
+
+    #[test]
+    fn test_analysis_finishes_after_planning_and_every_step() {
+        let document_id = Uuid::new_v4();
+        let plan = begin_analysis(None, document_id);
+        {
+            let mut tracked = PROGRESS.lock().unwrap();
+            tracked.get_mut(&document_id).unwrap().steps_started = 2;
+        }
+
+        // A step finishing while steps are still being started does not end analysis
+        drop(AnalysisStep { document_id });
+        drop(AnalysisStep { document_id });
+        assert_eq!(current(document_id).unwrap().status, ProgressStatus::Running);
+
+        drop(plan);
+        assert!(current(document_id).is_none());
+    }
+
+    #[test]
+    fn test_analysis_reports_finished_steps() {
+        let document_id = Uuid::new_v4();
+        drop(begin_analysis(None, document_id));
+        // Dropping the plan with no steps started ends analysis at once
+        assert!(current(document_id).is_none());
+
+        let plan = begin_analysis(None, document_id);
+        PROGRESS.lock().unwrap().get_mut(&document_id).unwrap().steps_started = 4;
+        drop(plan);
+        drop(AnalysisStep { document_id });
+
+        let progress = current(document_id).unwrap();
+        assert_eq!((progress.stage, progress.status, progress.percent), (ProgressStage::Analysis, ProgressStatus::Running, 25));
+        assert_eq!(progress.overall_percent, 77);
+    }
+}
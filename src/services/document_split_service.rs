@@ -0,0 +1,338 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeSet;
+use std::path::Path;
+use tracing::info;
+#[cfg(feature = "ocr")]
+use tracing::warn;
+
+use crate::db::Database;
+use crate::ingestion::document_ingestion::{
+    DeduplicationPolicy, DocumentIngestionRequest, DocumentIngestionService, IngestionResult,
+};
+use crate::models::{Document, SplitMethod};
+use crate::ocr::pdf_pages::TempPdf;
+use crate::services::file_service::FileService;
+use crate::services::llm::llm_service::{LLMService, PromptTemplate};
+
+/// Batch scans longer than this are not analysed for splitting
+const DEFAULT_MAX_PAGES: u32 = 500;
+/// Marker expected on separator sheets when the request does not name one
+const DEFAULT_SEPARATOR_TEXT: &str = "SEPARATOR";
+/// Resolution used to check scanned pages for ink
+#[cfg(feature = "ocr")]
+const BLANK_CHECK_DPI: u32 = 40;
+/// Resolution used to OCR scanned pages that have no text layer
+#[cfg(feature = "ocr")]
+const PAGE_OCR_DPI: u32 = 150;
+/// Pages with less dark-pixel coverage than this (margins excluded) are blank
+#[cfg(feature = "ocr")]
+const BLANK_INK_RATIO: f32 = 0.002;
+/// Characters of each page shown to the LLM for boundary detection
+const LLM_PAGE_SNIPPET_CHARS: usize = 400;
+
+const LLM_PROMPT: PromptTemplate = PromptTemplate::new("document_split", 1);
+
+/// Splits a single uploaded PDF that contains several stapled documents into
+/// child documents linked to the original upload
+pub struct DocumentSplitService {
+    db: Database,
+    file_service: FileService,
+    llm_service: LLMService,
+    max_pages: u32,
+}
+
+impl DocumentSplitService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        let llm_service = LLMService::new(db.get_pool().clone());
+        let max_pages = std::env::var("SPLIT_MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_PAGES);
+
+        Self { db, file_service, llm_service, max_pages }
+    }
+
+    /// Find the page ranges of the documents contained in a batch scan.
+    /// Returns the page count and the inclusive, 1-based ranges.
+    pub async fn detect_ranges(
+        &self,
+        document: &Document,
+        method: SplitMethod,
+        separator_text: Option<&str>,
+    ) -> Result<(u32, Vec<(u32, u32)>)> {
+        if document.mime_type != "application/pdf" {
+            return Err(anyhow!("Only PDF documents can be split"));
+        }
+
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let pdf = TempPdf::from_bytes(&data).await?;
+        let page_count = pdf.page_count().await?;
+        if page_count > self.max_pages {
+            return Err(anyhow!("Document has {} pages, splitting is limited to {}", page_count, self.max_pages));
+        }
+
+        let (starts, dropped) = match method {
+            SplitMethod::BlankPage => {
+                let mut blank = BTreeSet::new();
+                for page in 1..=page_count {
+                    if self.is_blank_page(&pdf, &data, page).await? {
+                        blank.insert(page);
+                    }
+                }
+                (BTreeSet::new(), blank)
+            }
+            SplitMethod::SeparatorSheet => {
+                let marker = separator_text
+                    .map(str::to_string)
+                    .or_else(|| std::env::var("SPLIT_SEPARATOR_TEXT").ok())
+                    .unwrap_or_else(|| DEFAULT_SEPARATOR_TEXT.to_string())
+                    .to_lowercase();
+                let mut separators = BTreeSet::new();
+                for page in 1..=page_count {
+                    let text = self.page_text(&pdf, &data, page).await?;
+                    if text.to_lowercase().contains(&marker) {
+                        separators.insert(page);
+                    }
+                }
+                (BTreeSet::new(), separators)
+            }
+            SplitMethod::Llm => {
+                if !self.llm_service.chat_enabled() {
+                    return Err(anyhow!("LLM boundary detection requires LLM_API_KEY"));
+                }
+                let mut snippets = Vec::with_capacity(page_count as usize);
+                for page in 1..=page_count {
+                    let text = self.page_text(&pdf, &data, page).await?;
+                    snippets.push(text.chars().take(LLM_PAGE_SNIPPET_CHARS).collect::<String>());
+                }
+                (self.llm_document_starts(&snippets).await?, BTreeSet::new())
+            }
+        };
+
+        Ok((page_count, page_ranges(page_count, &starts, &dropped)))
+    }
+
+    /// Create one child document per page range. The caller is responsible for
+    /// queueing OCR on the returned documents.
+    pub async fn create_children(
+        &self,
+        parent: &Document,
+        method: SplitMethod,
+        ranges: &[(u32, u32)],
+    ) -> Result<Vec<(u32, u32, Document)>> {
+        let data = self.file_service.read_file(&parent.file_path).await?;
+        let pdf = TempPdf::from_bytes(&data).await?;
+        let ingestion_service = DocumentIngestionService::new(self.db.clone(), self.file_service.clone());
+
+        let stem = Path::new(&parent.original_filename)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "document".to_string());
+
+        let mut children = Vec::with_capacity(ranges.len());
+        for (index, (start, end)) in ranges.iter().copied().enumerate() {
+            let pages: Vec<u32> = (start..=end).collect();
+            let part_data = pdf.extract_pages(&pages).await?;
+            let filename = format!("{}_part{}.pdf", stem, index + 1);
+
+            let request = DocumentIngestionRequest {
+                filename: filename.clone(),
+                original_filename: filename,
+                file_data: part_data,
+                mime_type: "application/pdf".to_string(),
+                user_id: parent.user_id,
+                deduplication_policy: DeduplicationPolicy::AllowDuplicateContent,
+                source_type: Some("split".to_string()),
+                source_id: None,
+                original_created_at: parent.original_created_at,
+                original_modified_at: parent.original_modified_at,
+                source_path: None,
+                file_permissions: None,
+                file_owner: None,
+                file_group: None,
+                source_metadata: Some(serde_json::json!({
+                    "split_from": parent.id,
+                    "split_method": method,
+                    "start_page": start,
+                    "end_page": end,
+                })),
+            };
+
+            let child = match ingestion_service.ingest_document(request).await {
+                Ok(IngestionResult::Created(document)) | Ok(IngestionResult::ExistingDocument(document)) => document,
+                Ok(other) => return Err(anyhow!("Split part {} was not stored: {:?}", index + 1, other)),
+                Err(e) => return Err(anyhow!("Failed to store split part {}: {}", index + 1, e)),
+            };
+
+            self.db
+                .create_document_split(parent.id, child.id, start as i32, end as i32, method)
+                .await?;
+            children.push((start, end, child));
+        }
+
+        info!("Split document {} into {} documents using {}", parent.id, children.len(), method);
+        Ok(children)
+    }
+
+    async fn page_text(&self, pdf: &TempPdf, data: &[u8], page: u32) -> Result<String> {
+        let text = pdf.page_text(page).await?;
+        if text.chars().any(char::is_alphanumeric) {
+            return Ok(text);
+        }
+        self.ocr_page(data, page).await
+    }
+
+    #[cfg(feature = "ocr")]
+    async fn ocr_page(&self, data: &[u8], page: u32) -> Result<String> {
+        use crate::ocr::page_images;
+
+        let rendered = page_images::render_page_range(data, "application/pdf", PAGE_OCR_DPI, page, page).await?;
+        match rendered.first() {
+            Some(image) => page_images::ocr_image(image, "eng").await.or_else(|e| {
+                warn!("OCR of page {} failed during split detection: {}", page, e);
+                Ok(String::new())
+            }),
+            None => Ok(String::new()),
+        }
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    async fn ocr_page(&self, _data: &[u8], _page: u32) -> Result<String> {
+        Ok(String::new())
+    }
+
+    async fn is_blank_page(&self, pdf: &TempPdf, data: &[u8], page: u32) -> Result<bool> {
+        let text = pdf.page_text(page).await?;
+        if text.chars().any(char::is_alphanumeric) {
+            return Ok(false);
+        }
+        self.has_no_ink(data, page).await
+    }
+
+    #[cfg(feature = "ocr")]
+    async fn has_no_ink(&self, data: &[u8], page: u32) -> Result<bool> {
+        use crate::ocr::page_images;
+
+        let rendered = page_images::render_page_range(data, "application/pdf", BLANK_CHECK_DPI, page, page).await?;
+        Ok(rendered.first().map(|image| ink_ratio(&image.to_luma8()) < BLANK_INK_RATIO).unwrap_or(true))
+    }
+
+    /// Without rasterization a page without a text layer is treated as blank
+    #[cfg(not(feature = "ocr"))]
+    async fn has_no_ink(&self, _data: &[u8], _page: u32) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn llm_document_starts(&self, snippets: &[String]) -> Result<BTreeSet<u32>> {
+        let mut prompt = String::from(
+            "The following pages come from one scan that contains several separate documents \
+            (letters, invoices, forms). For each page you are given its first lines. \
+            Decide on which pages a new document begins. Return ONLY a JSON array of page numbers, \
+            for example [1, 4, 5]. Page 1 always begins a document.\n\n",
+        );
+        for (index, snippet) in snippets.iter().enumerate() {
+            prompt.push_str(&format!("--- Page {} ---\n{}\n\n", index + 1, snippet.trim()));
+        }
+
+        let answer = self
+            .llm_service
+            .complete(LLM_PROMPT, "You segment batch scans into individual documents.", &prompt)
+            .await
+            .map_err(|e| anyhow!(e))?;
+
+        parse_page_list(&answer, snippets.len() as u32)
+            .ok_or_else(|| anyhow!("LLM returned an invalid page list: {}", answer))
+    }
+}
+
+/// Turn document start pages and dropped (blank/separator) pages into
+/// inclusive page ranges. Dropped pages also end the current document.
+pub fn page_ranges(page_count: u32, starts: &BTreeSet<u32>, dropped: &BTreeSet<u32>) -> Vec<(u32, u32)> {
+    let mut ranges = Vec::new();
+    let mut current: Option<(u32, u32)> = None;
+
+    for page in 1..=page_count {
+        if dropped.contains(&page) {
+            if let Some(range) = current.take() {
+                ranges.push(range);
+            }
+            continue;
+        }
+
+        match current {
+            Some(range) if starts.contains(&page) => {
+                ranges.push(range);
+                current = Some((page, page));
+            }
+            Some((start, _)) => current = Some((start, page)),
+            None => current = Some((page, page)),
+        }
+    }
+
+    if let Some(range) = current {
+        ranges.push(range);
+    }
+    ranges
+}
+
+fn parse_page_list(answer: &str, page_count: u32) -> Option<BTreeSet<u32>> {
+    let pages: Vec<u32> = serde_json::from_str(answer.trim()).ok()?;
+    Some(pages.into_iter().filter(|p| (1..=page_count).contains(p)).collect())
+}
+
+/// Fraction of dark pixels, ignoring a 5% margin where scanner edges show up
+#[cfg(feature = "ocr")]
+fn ink_ratio(image: &image::GrayImage) -> f32 {
+    let (width, height) = image.dimensions();
+    let (margin_x, margin_y) = (width / 20, height / 20);
+    let mut dark = 0u32;
+    let mut total = 0u32;
+
+    for y in margin_y..height.saturating_sub(margin_y) {
+        for x in margin_x..width.saturating_sub(margin_x) {
+            total += 1;
+            if image.get_pixel(x, y).0[0] < 100 {
+                dark += 1;
+            }
+        }
+    }
+
+    if total == 0 {
+        0.0
+    } else {
+        dark as f32 / total as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(pages: &[u32]) -> BTreeSet<u32> {
+        pages.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_dropped_pages_separate_documents() {
+        let ranges = page_ranges(8, &set(&[]), &set(&[3, 6, 7]));
+        assert_eq!(ranges, vec![(1, 2), (4, 5), (8, 8)]);
+    }
+
+    #[test]
+    fn test_start_pages_begin_new_documents() {
+        let ranges = page_ranges(6, &set(&[1, 3, 5]), &set(&[]));
+        assert_eq!(ranges, vec![(1, 2), (3, 4), (5, 6)]);
+    }
+
+    #[test]
+    fn test_leading_and_trailing_separators_are_ignored() {
+        let ranges = page_ranges(5, &set(&[]), &set(&[1, 5]));
+        assert_eq!(ranges, vec![(2, 4)]);
+    }
+
+    #[test]
+    fn test_parse_page_list_discards_out_of_range_pages() {
+        assert_eq!(parse_page_list("[1, 3, 9, 0]", 4), Some(set(&[1, 3])));
+        assert_eq!(parse_page_list("pages 1 and 3", 4), None);
+    }
+}
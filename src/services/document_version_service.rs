@@ -0,0 +1,212 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::document_versions::NewDocumentContents;
+use crate::db::Database;
+use crate::models::{DiffLine, DiffLineKind, Document};
+use crate::services::file_service::FileService;
+use crate::services::legal_hold_service;
+use crate::services::thumbnail_service::requeue_thumbnail;
+use crate::storage;
+
+/// Line pairs compared at most when diffing; larger changes are shown as a whole
+/// block removed and a whole block added
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Keeps earlier contents of a document when a changed file arrives for it, and
+/// brings earlier contents back
+pub struct DocumentVersionService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl DocumentVersionService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Make `data` the document's contents, keeping the current contents as its next
+    /// version. The document is left pending OCR.
+    pub async fn add_version(
+        &self,
+        document: &Document,
+        data: &[u8],
+        original_modified_at: Option<DateTime<Utc>>,
+    ) -> Result<Document> {
+        legal_hold_service::ensure_not_held(&self.db, document.id).await?;
+        let version_id = Uuid::new_v4();
+        // Shared content is handed over to the version instead of being copied
+        let current = if storage::is_blob_path(&document.file_path) {
+            None
+        } else {
+            Some(self.file_service.read_file(&document.file_path).await?)
+        };
+        let version_file_path = match &current {
+            Some(current) => {
+                self.file_service
+                    .save_document_file(document.user_id, version_id, &document.filename, current)
+                    .await?
+            }
+            None => document.file_path.clone(),
+        };
+
+        // Without deduplication this is stored under the document's id, overwriting
+        // the current file
+        let file_hash = format!("{:x}", Sha256::digest(data));
+        let file_path = self
+            .file_service
+            .store_document_content(document.user_id, document.id, &document.filename, data, &file_hash)
+            .await?;
+        self.file_service.invalidate_thumbnail(&document.file_path).await;
+        self.file_service.invalidate_thumbnail(&file_path).await;
+
+        let contents = NewDocumentContents {
+            file_path: &file_path,
+            file_size: data.len() as i64,
+            file_hash: &file_hash,
+            original_modified_at,
+        };
+        match self.db.store_document_version(document.id, version_id, &version_file_path, &contents).await {
+            Ok((version, updated)) => {
+                info!("Stored version {} of document {}", version.version_number, document.id);
+                // The version has its own copy; a file the storage layout put in the
+                // library is not overwritten by the new contents
+                if self.file_service.is_library_path(&document.file_path) {
+                    if let Err(e) = self.file_service.delete_stored_file(&document.file_path).await {
+                        warn!("Failed to delete previous file {}: {}", document.file_path, e);
+                    }
+                }
+                requeue_thumbnail(&self.db, document.id).await;
+                Ok(updated)
+            }
+            Err(e) => {
+                if let Err(release_err) = self.file_service.release_blob(&file_path).await {
+                    warn!("Failed to release blob {}: {}", file_path, release_err);
+                }
+                if let Some(current) = &current {
+                    // Put the current contents back so the file matches the unchanged row
+                    if let Err(restore_err) = self
+                        .file_service
+                        .save_document_file(document.user_id, document.id, &document.filename, current)
+                        .await
+                    {
+                        warn!("Failed to restore file of document {}: {}", document.id, restore_err);
+                    }
+                    if let Err(delete_err) = self
+                        .file_service
+                        .delete_version_file(document.user_id, version_id, &document.filename, &version_file_path)
+                        .await
+                    {
+                        warn!("Failed to delete unused version file {}: {}", version_file_path, delete_err);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Bring back the contents of an earlier version, keeping the current contents as
+    /// a new version. None when the version does not exist.
+    pub async fn restore_version(&self, document: &Document, version_number: i32) -> Result<Option<Document>> {
+        let Some(version) = self.db.get_document_version(document.id, version_number).await? else {
+            return Ok(None);
+        };
+
+        let data = self.file_service.read_file(&version.file_path).await?;
+        let restored = self.add_version(document, &data, version.original_modified_at).await?;
+        info!("Restored document {} to version {}", document.id, version_number);
+        Ok(Some(restored))
+    }
+}
+
+/// Line diff turning `old` into `new`, keeping unchanged lines
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let line = |kind, text: &str| DiffLine { kind, text: text.to_string() };
+    let mut lines: Vec<DiffLine> = old[..prefix].iter().map(|text| line(DiffLineKind::Unchanged, *text)).collect();
+
+    if (old_middle.len() + 1) * (new_middle.len() + 1) > MAX_DIFF_CELLS {
+        lines.extend(old_middle.iter().map(|text| line(DiffLineKind::Removed, *text)));
+        lines.extend(new_middle.iter().map(|text| line(DiffLineKind::Added, *text)));
+    } else {
+        // Longest common subsequence lengths of every pair of suffixes
+        let columns = new_middle.len() + 1;
+        let mut common = vec![0u32; (old_middle.len() + 1) * columns];
+        for i in (0..old_middle.len()).rev() {
+            for j in (0..new_middle.len()).rev() {
+                common[i * columns + j] = if old_middle[i] == new_middle[j] {
+                    common[(i + 1) * columns + j + 1] + 1
+                } else {
+                    common[(i + 1) * columns + j].max(common[i * columns + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_middle.len() || j < new_middle.len() {
+            if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+                lines.push(line(DiffLineKind::Unchanged, old_middle[i]));
+                i += 1;
+                j += 1;
+            } else if j == new_middle.len()
+                || (i < old_middle.len() && common[(i + 1) * columns + j] >= common[i * columns + j + 1])
+            {
+                lines.push(line(DiffLineKind::Removed, old_middle[i]));
+                i += 1;
+            } else {
+                lines.push(line(DiffLineKind::Added, new_middle[j]));
+                j += 1;
+            }
+        }
+    }
+
+    lines.extend(old[old.len() - suffix..].iter().map(|text| line(DiffLineKind::Unchanged, *text)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(lines: &[DiffLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| {
+                let marker = match line.kind {
+                    DiffLineKind::Unchanged => ' ',
+                    DiffLineKind::Added => '+',
+                    DiffLineKind::Removed => '-',
+                };
+                format!("{}{}", marker, line.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let old = "Invoice 1042\nAmount: 120.00\nDue: 2024-03-01\nThank you";
+        let new = "Invoice 1042\nAmount: 135.00\nDue: 2024-03-01\nPaid\nThank you";
+
+        assert_eq!(
+            render(&diff_lines(old, new)),
+            vec![" Invoice 1042", "-Amount: 120.00", "+Amount: 135.00", " Due: 2024-03-01", "+Paid", " Thank you"]
+        );
+        assert_eq!(render(&diff_lines("", "a")), vec!["+a"]);
+        assert_eq!(render(&diff_lines("a\nb", "a\nb")), vec![" a", " b"]);
+    }
+}
@@ -0,0 +1,221 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::models::{DropboxSourceConfig, OutboundScope};
+use crate::services::cloud_drive::{relative_to_root, CloudDriveClient, CloudDriveError, CloudFile, DeltaPage, OAuthToken};
+use crate::services::outbound_limits;
+
+const TOKEN_URL: &str = "https://api.dropboxapi.com/oauth2/token";
+const API_URL: &str = "https://api.dropboxapi.com/2";
+const CONTENT_URL: &str = "https://content.dropboxapi.com/2";
+
+/// Dropbox source client built on `files/list_folder` and its cursor
+pub struct DropboxService {
+    config: DropboxSourceConfig,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: i64,
+}
+
+#[derive(Deserialize)]
+struct ListFolderResponse {
+    entries: Vec<Entry>,
+    cursor: String,
+    has_more: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = ".tag", rename_all = "lowercase")]
+enum Entry {
+    File {
+        id: String,
+        name: String,
+        path_display: Option<String>,
+        size: i64,
+        server_modified: Option<DateTime<Utc>>,
+        content_hash: Option<String>,
+    },
+    Folder {},
+    Deleted {},
+}
+
+impl DropboxService {
+    pub fn new(config: DropboxSourceConfig) -> Result<Self> {
+        if config.app_key.trim().is_empty() || config.app_secret.trim().is_empty() {
+            return Err(anyhow!("Dropbox app_key and app_secret are required"));
+        }
+        if config.refresh_token.trim().is_empty() {
+            return Err(anyhow!("Dropbox refresh_token is required"));
+        }
+        crate::services::cloud_drive::PathFilter::new(&config.include_patterns, &config.exclude_patterns)?;
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(300))
+            .build()?;
+
+        Ok(Self { config, client })
+    }
+
+    /// Dropbox addresses the root folder as "" rather than "/"
+    fn root_path(&self) -> String {
+        let root = self.config.root_path.trim().trim_end_matches('/');
+        if root.is_empty() {
+            String::new()
+        } else if root.starts_with('/') {
+            root.to_string()
+        } else {
+            format!("/{}", root)
+        }
+    }
+
+    async fn api_call(&self, access_token: &str, endpoint: &str, body: serde_json::Value) -> Result<Response> {
+        let response = self.client
+            .post(format!("{}/{}", API_URL, endpoint))
+            .bearer_auth(access_token)
+            .json(&body)
+            .send()
+            .await?;
+        check_response(response).await
+    }
+}
+
+/// Maps the statuses the sync recovers from to `CloudDriveError`
+async fn check_response(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(CloudDriveError::Unauthorized.into());
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    // An expired list_folder cursor is a 409 whose error summary starts with "reset/"
+    if status == StatusCode::CONFLICT && body.contains("\"reset/") {
+        return Err(CloudDriveError::CursorExpired.into());
+    }
+    Err(anyhow!("Dropbox API responded with {}: {}", status, body))
+}
+
+#[async_trait]
+impl CloudDriveClient for DropboxService {
+    fn provider_name(&self) -> &'static str {
+        "Dropbox"
+    }
+
+    async fn refresh_access_token(&self, refresh_token: &str) -> Result<OAuthToken> {
+        let _permit = outbound_limits::acquire(OutboundScope::Dropbox).await;
+        let response = self.client
+            .post(TOKEN_URL)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("client_id", self.config.app_key.as_str()),
+                ("client_secret", self.config.app_secret.as_str()),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Dropbox token refresh failed with {}: {}", status, body));
+        }
+
+        // Dropbox refresh tokens do not rotate
+        let token: TokenResponse = response.json().await?;
+        Ok(OAuthToken::from_expires_in(token.access_token, token.expires_in, None))
+    }
+
+    async fn delta(&self, access_token: &str, cursor: Option<&str>) -> Result<DeltaPage> {
+        let _permit = outbound_limits::acquire(OutboundScope::Dropbox).await;
+        let response = match cursor {
+            Some(cursor) => {
+                self.api_call(access_token, "files/list_folder/continue", json!({ "cursor": cursor })).await?
+            }
+            None => {
+                self.api_call(access_token, "files/list_folder", json!({
+                    "path": self.root_path(),
+                    "recursive": true,
+                    "include_deleted": true,
+                })).await?
+            }
+        };
+        let listing: ListFolderResponse = response.json().await?;
+
+        let root = self.root_path();
+        let mut files = Vec::new();
+        let mut deleted_count = 0;
+        for entry in listing.entries {
+            match entry {
+                Entry::File { id, name, path_display, size, server_modified, content_hash } => {
+                    let display_path = path_display.unwrap_or_else(|| name.clone());
+                    let Some(relative_path) = relative_to_root(&display_path, &root) else {
+                        continue;
+                    };
+                    files.push(CloudFile {
+                        id,
+                        relative_path,
+                        display_path,
+                        name,
+                        size,
+                        modified_at: server_modified,
+                        content_hash,
+                    });
+                }
+                Entry::Deleted {} => deleted_count += 1,
+                Entry::Folder {} => {}
+            }
+        }
+
+        Ok(DeltaPage {
+            files,
+            deleted_count,
+            cursor: listing.cursor,
+            has_more: listing.has_more,
+        })
+    }
+
+    async fn download(&self, access_token: &str, file: &CloudFile) -> Result<Vec<u8>> {
+        let _permit = outbound_limits::acquire(OutboundScope::Dropbox).await;
+        let response = self.client
+            .post(format!("{}/files/download", CONTENT_URL))
+            .bearer_auth(access_token)
+            .header("Dropbox-API-Arg", json!({ "path": file.id }).to_string())
+            .send()
+            .await?;
+        let response = check_response(response).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn describe(&self, access_token: &str) -> Result<String> {
+        let _permit = outbound_limits::acquire(OutboundScope::Dropbox).await;
+        #[derive(Deserialize)]
+        struct Account {
+            email: String,
+        }
+
+        let response = self.client
+            .post(format!("{}/users/get_current_account", API_URL))
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+        let account: Account = check_response(response).await?.json().await?;
+
+        let root = self.root_path();
+        Ok(format!(
+            "Connected to Dropbox as {}, syncing {}",
+            account.email,
+            if root.is_empty() { "/" } else { root.as_str() }
+        ))
+    }
+}
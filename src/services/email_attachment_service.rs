@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::db::Database;
+use crate::ingestion::document_ingestion::{
+    DeduplicationPolicy, DocumentIngestionRequest, DocumentIngestionService, IngestionResult,
+};
+use crate::models::{Document, EventType};
+use crate::ocr::email_parser;
+use crate::ocr::queue::OcrQueueService;
+use crate::services::event_service::EventService;
+use crate::services::file_service::FileService;
+
+/// Turns the attachments of an ingested email into documents of their own,
+/// linked to the email and queued for OCR
+pub struct EmailAttachmentService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl EmailAttachmentService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Extract the attachments of `email`. Emails whose attachments were already
+    /// extracted (e.g. on OCR retry) are left alone.
+    pub async fn extract_attachments(&self, email: &Document) -> Result<Vec<Document>> {
+        if !email_parser::is_email(&email.mime_type, &email.original_filename) {
+            return Err(anyhow!("Document {} is not an email", email.id));
+        }
+        if !self.db.get_document_attachments(email.id).await?.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let data = self.file_service.read_file(&email.file_path).await?;
+        let parsed = email_parser::load_email(&data, &email.mime_type, &email.original_filename).await?;
+        if parsed.attachments.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ingestion_service = DocumentIngestionService::new(self.db.clone(), self.file_service.clone());
+        let queue_service = OcrQueueService::new(
+            self.db.clone(),
+            self.db.get_pool().clone(),
+            1,
+            Arc::new(self.file_service.clone()),
+        );
+        let event_service = EventService::new(self.db.clone());
+
+        let mut children = Vec::with_capacity(parsed.attachments.len());
+        for (index, attachment) in parsed.attachments.into_iter().enumerate() {
+            let position = index as i32 + 1;
+            let request = DocumentIngestionRequest {
+                filename: attachment.filename.clone(),
+                original_filename: attachment.filename.clone(),
+                file_data: attachment.data,
+                mime_type: attachment.mime_type,
+                user_id: email.user_id,
+                deduplication_policy: DeduplicationPolicy::AllowDuplicateContent,
+                source_type: Some("email_attachment".to_string()),
+                source_id: email.source_id,
+                original_created_at: parsed.date,
+                original_modified_at: parsed.date,
+                source_path: None,
+                file_permissions: None,
+                file_owner: None,
+                file_group: None,
+                source_metadata: Some(serde_json::json!({
+                    "email_document_id": email.id,
+                    "email_subject": parsed.subject,
+                    "email_from": parsed.from,
+                })),
+            };
+
+            let child = match ingestion_service.ingest_document(request).await {
+                Ok(IngestionResult::Created(document)) | Ok(IngestionResult::ExistingDocument(document)) => document,
+                Ok(other) => {
+                    warn!("Attachment '{}' of email {} was not stored: {:?}", attachment.filename, email.id, other);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Failed to store attachment '{}' of email {}: {}", attachment.filename, email.id, e);
+                    continue;
+                }
+            };
+
+            self.db
+                .create_document_attachment(email.id, child.id, &attachment.filename, position)
+                .await?;
+
+            if let Err(e) = queue_service.enqueue_document(child.id, 5, child.file_size).await {
+                warn!("Failed to enqueue attachment document {} for OCR: {}", child.id, e);
+            }
+            event_service
+                .publish_best_effort(
+                    EventType::DocumentCreated,
+                    Some(child.user_id),
+                    Some(child.id),
+                    serde_json::json!({
+                        "document_id": child.id,
+                        "filename": child.filename,
+                        "mime_type": child.mime_type,
+                        "file_size": child.file_size,
+                        "source_type": child.source_type,
+                        "email_document_id": email.id,
+                    }),
+                )
+                .await;
+
+            children.push(child);
+        }
+
+        info!("Extracted {} attachments from email {}", children.len(), email.id);
+        Ok(children)
+    }
+}
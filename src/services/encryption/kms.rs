@@ -0,0 +1,206 @@
+use anyhow::{anyhow, Result};
+use base64ct::{Base64, Encoding};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use std::time::Duration;
+
+use super::{open_with_key, seal_with_key, KEY_LEN};
+
+/// Additional data bound to keys wrapped by the master key
+const MASTER_WRAP_AAD: &[u8] = b"readur-data-key";
+
+/// The key encryption key a data key is wrapped with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KmsKeyRef {
+    /// The server master key from `ENCRYPTION_MASTER_KEY`
+    Master,
+    /// A customer-managed HashiCorp Vault transit key, referenced as `vault:<key name>`
+    VaultTransit(String),
+}
+
+impl KmsKeyRef {
+    /// Parse a stored or requested reference; None or an empty string is the master key
+    pub fn parse(kms_key_ref: Option<&str>) -> Result<Self> {
+        let Some(reference) = kms_key_ref.map(str::trim).filter(|r| !r.is_empty()) else {
+            return Ok(Self::Master);
+        };
+
+        match reference.split_once(':') {
+            Some(("vault", name))
+                if !name.is_empty()
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') =>
+            {
+                Ok(Self::VaultTransit(name.to_string()))
+            }
+            _ => Err(anyhow!(
+                "Unsupported KMS key reference '{}', expected vault:<transit key name>",
+                reference
+            )),
+        }
+    }
+
+    /// Value of the `provider` column
+    pub fn provider(&self) -> &'static str {
+        match self {
+            Self::Master => "local",
+            Self::VaultTransit(_) => "vault",
+        }
+    }
+
+    /// Value of the `kms_key_ref` column
+    pub fn to_ref_string(&self) -> Option<String> {
+        match self {
+            Self::Master => None,
+            Self::VaultTransit(name) => Some(format!("vault:{}", name)),
+        }
+    }
+}
+
+/// Wraps and unwraps data keys with the master key or a customer-managed KMS key
+pub struct KeyManager {
+    master_key: [u8; KEY_LEN],
+    vault: Option<VaultTransit>,
+}
+
+struct VaultTransit {
+    client: Client,
+    addr: String,
+    token: String,
+    mount: String,
+}
+
+#[derive(Deserialize)]
+struct VaultResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct VaultEncrypted {
+    ciphertext: String,
+}
+
+#[derive(Deserialize)]
+struct VaultDecrypted {
+    plaintext: String,
+}
+
+impl KeyManager {
+    /// None when `ENCRYPTION_MASTER_KEY` is not set, i.e. encryption is disabled.
+    /// Vault transit keys become available when `VAULT_ADDR` and `VAULT_TOKEN` are set.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(encoded) = std::env::var("ENCRYPTION_MASTER_KEY").ok().filter(|v| !v.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let decoded = Base64::decode_vec(encoded.trim())
+            .map_err(|_| anyhow!("ENCRYPTION_MASTER_KEY is not valid base64"))?;
+        let master_key: [u8; KEY_LEN] = decoded
+            .try_into()
+            .map_err(|_| anyhow!("ENCRYPTION_MASTER_KEY must be {} bytes, e.g. from `openssl rand -base64 32`", KEY_LEN))?;
+
+        let vault = match (std::env::var("VAULT_ADDR"), std::env::var("VAULT_TOKEN")) {
+            (Ok(addr), Ok(token)) if !addr.trim().is_empty() && !token.trim().is_empty() => Some(VaultTransit {
+                client: Client::builder().timeout(Duration::from_secs(10)).build()?,
+                addr: addr.trim().trim_end_matches('/').to_string(),
+                token: token.trim().to_string(),
+                mount: std::env::var("VAULT_TRANSIT_MOUNT").unwrap_or_else(|_| "transit".to_string()),
+            }),
+            _ => None,
+        };
+
+        Ok(Some(Self { master_key, vault }))
+    }
+
+    pub fn vault_enabled(&self) -> bool {
+        self.vault.is_some()
+    }
+
+    pub async fn wrap(&self, key_ref: &KmsKeyRef, data_key: &[u8]) -> Result<Vec<u8>> {
+        match key_ref {
+            KmsKeyRef::Master => seal_with_key(&self.master_key, MASTER_WRAP_AAD, data_key),
+            KmsKeyRef::VaultTransit(name) => {
+                let vault = self.vault()?;
+                let response: VaultResponse<VaultEncrypted> = vault
+                    .post(&format!("encrypt/{}", name), json!({ "plaintext": Base64::encode_string(data_key) }))
+                    .await?;
+                Ok(response.data.ciphertext.into_bytes())
+            }
+        }
+    }
+
+    pub async fn unwrap(&self, key_ref: &KmsKeyRef, wrapped_key: &[u8]) -> Result<Vec<u8>> {
+        match key_ref {
+            KmsKeyRef::Master => open_with_key(&self.master_key, MASTER_WRAP_AAD, wrapped_key),
+            KmsKeyRef::VaultTransit(name) => {
+                let vault = self.vault()?;
+                let ciphertext = std::str::from_utf8(wrapped_key)
+                    .map_err(|_| anyhow!("Wrapped key is not a Vault ciphertext"))?;
+                let response: VaultResponse<VaultDecrypted> = vault
+                    .post(&format!("decrypt/{}", name), json!({ "ciphertext": ciphertext }))
+                    .await?;
+                Base64::decode_vec(&response.data.plaintext)
+                    .map_err(|_| anyhow!("Vault returned an invalid plaintext"))
+            }
+        }
+    }
+
+    fn vault(&self) -> Result<&VaultTransit> {
+        self.vault
+            .as_ref()
+            .ok_or_else(|| anyhow!("Vault is not configured; set VAULT_ADDR and VAULT_TOKEN to use vault: key references"))
+    }
+}
+
+impl VaultTransit {
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: serde_json::Value) -> Result<T> {
+        let response = self.client
+            .post(format!("{}/v1/{}/{}", self.addr, self.mount, path))
+            .header("X-Vault-Token", &self.token)
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Vault transit {} failed with {}: {}", path, status, body));
+        }
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kms_key_ref() {
+        assert_eq!(KmsKeyRef::parse(None).unwrap(), KmsKeyRef::Master);
+        assert_eq!(KmsKeyRef::parse(Some(" ")).unwrap(), KmsKeyRef::Master);
+        assert_eq!(
+            KmsKeyRef::parse(Some("vault:tenant-a")).unwrap(),
+            KmsKeyRef::VaultTransit("tenant-a".to_string())
+        );
+        assert!(KmsKeyRef::parse(Some("vault:")).is_err());
+        assert!(KmsKeyRef::parse(Some("vault:../sys")).is_err());
+        assert!(KmsKeyRef::parse(Some("arn:aws:kms:eu-west-1:1:key/x")).is_err());
+
+        let vault = KmsKeyRef::VaultTransit("tenant-a".to_string());
+        assert_eq!(vault.to_ref_string().as_deref(), Some("vault:tenant-a"));
+        assert_eq!(vault.provider(), "vault");
+    }
+
+    #[tokio::test]
+    async fn test_master_key_wrapping() {
+        let keys = KeyManager { master_key: [7; KEY_LEN], vault: None };
+        let data_key = [42u8; KEY_LEN];
+
+        let wrapped = keys.wrap(&KmsKeyRef::Master, &data_key).await.unwrap();
+        assert_ne!(&wrapped[..], &data_key[..]);
+        assert_eq!(keys.unwrap(&KmsKeyRef::Master, &wrapped).await.unwrap(), data_key.to_vec());
+
+        let other = KeyManager { master_key: [8; KEY_LEN], vault: None };
+        assert!(other.unwrap(&KmsKeyRef::Master, &wrapped).await.is_err());
+        assert!(keys.wrap(&KmsKeyRef::VaultTransit("x".to_string()), &data_key).await.is_err());
+    }
+}
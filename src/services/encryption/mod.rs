@@ -0,0 +1,457 @@
+//! Per-workspace and per-user envelope encryption of stored files and sensitive columns.
+//!
+//! Every workspace, and every user for their personal data, has a data encryption key
+//! (DEK) that is stored wrapped by a key encryption key: the server master key or a
+//! customer-managed KMS key. Encrypted data carries the id of the DEK version it was
+//! sealed with, so rotation only changes which version seals new data.
+
+pub mod kms;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Result};
+use base64ct::{Base64, Encoding};
+use rand::RngCore;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::RwLock;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::db::{workspaces::WorkspaceScoped, Database};
+use crate::models::{EncryptionKey, KeyOwner};
+use crate::services::file_service::FileService;
+use crate::services::workspace_service;
+use crate::storage::azure::AZURE_PATH_PREFIX;
+use kms::{KeyManager, KmsKeyRef};
+
+pub const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+/// Prefix of encrypted files: magic, then the DEK id, then the nonce
+const MAGIC: &[u8] = b"RDENC1";
+const HEADER_LEN: usize = MAGIC.len() + 16 + NONCE_LEN;
+/// Prefix of encrypted column values, followed by the base64 of an encrypted blob
+const FIELD_PREFIX: &str = "enc:v1:";
+/// Keys of source configurations whose values are credentials, sealed at rest
+const SECRET_CONFIG_KEYS: &[&str] = &[
+    "password",
+    "secret_access_key",
+    "webhook_secret",
+    "app_secret",
+    "client_secret",
+    "refresh_token",
+    "private_key",
+    "private_key_passphrase",
+];
+
+static INSTALLED: OnceLock<Arc<EncryptionService>> = OnceLock::new();
+
+/// Make the service available to the database layer, which seals and opens source
+/// credentials as it writes and reads them
+pub fn install(service: Arc<EncryptionService>) {
+    let _ = INSTALLED.set(service);
+}
+
+/// The service of this process, when encryption is enabled
+pub fn installed() -> Option<&'static Arc<EncryptionService>> {
+    INSTALLED.get()
+}
+
+/// Whether `data` was produced by `EncryptionService::encrypt_for`
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.len() >= HEADER_LEN && data.starts_with(MAGIC)
+}
+
+/// The key version an encrypted blob was sealed with
+pub fn sealed_key_id(data: &[u8]) -> Option<Uuid> {
+    if !is_encrypted(data) {
+        return None;
+    }
+    Uuid::from_slice(&data[MAGIC.len()..MAGIC.len() + 16]).ok()
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// AES-256-GCM with a random nonce prepended to the ciphertext
+pub(crate) fn seal_with_key(key: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| anyhow!("Invalid encryption key length"))?;
+    let nonce = random_bytes::<NONCE_LEN>();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| anyhow!("Encryption failed"))?;
+
+    let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub(crate) fn open_with_key(key: &[u8], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(anyhow!("Encrypted data is truncated"));
+    }
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| anyhow!("Invalid encryption key length"))?;
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| anyhow!("Decryption failed: wrong key or corrupted data"))
+}
+
+/// Seal `plaintext` into the file format: magic, key id and nonce, then the
+/// ciphertext. The magic and key id are authenticated as additional data.
+fn seal_blob(key_id: Uuid, data_key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(key_id.as_bytes());
+
+    let sealed = seal_with_key(data_key, &header, plaintext)?;
+    header.extend_from_slice(&sealed);
+    Ok(header)
+}
+
+fn open_blob(data_key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let aad_len = MAGIC.len() + 16;
+    open_with_key(data_key, &data[..aad_len], &data[aad_len..])
+}
+
+/// The key version a column value written by `EncryptionService::encrypt_field` was
+/// sealed with
+fn field_key_id(value: &str) -> Option<Uuid> {
+    let sealed = Base64::decode_vec(value.strip_prefix(FIELD_PREFIX)?).ok()?;
+    sealed_key_id(&sealed)
+}
+
+/// The credential values of a source configuration, at any depth
+fn secret_values(config: &mut Value) -> Vec<&mut String> {
+    let mut secrets = Vec::new();
+    match config {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let is_secret = SECRET_CONFIG_KEYS.contains(&key.as_str())
+                    && matches!(value, Value::String(secret) if !secret.is_empty());
+                if !is_secret {
+                    secrets.extend(secret_values(value));
+                } else if let Value::String(secret) = value {
+                    secrets.push(secret);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                secrets.extend(secret_values(item));
+            }
+        }
+        _ => {}
+    }
+    secrets
+}
+
+/// Whether every credential of a source configuration is sealed with `key_id`
+pub fn secrets_sealed_with(config: &Value, key_id: Uuid) -> bool {
+    let mut config = config.clone();
+    secret_values(&mut config).iter().all(|secret| field_key_id(secret) == Some(key_id))
+}
+
+pub struct EncryptionService {
+    db: Database,
+    keys: KeyManager,
+    /// Unwrapped data keys by key id
+    data_keys: RwLock<HashMap<Uuid, Arc<[u8; KEY_LEN]>>>,
+}
+
+impl EncryptionService {
+    /// None unless `ENCRYPTION_MASTER_KEY` is set
+    pub fn from_env(db: Database) -> Result<Option<Self>> {
+        Ok(KeyManager::from_env()?.map(|keys| Self {
+            db,
+            keys,
+            data_keys: RwLock::new(HashMap::new()),
+        }))
+    }
+
+    /// Summary for startup logs
+    pub fn describe(&self) -> &'static str {
+        if self.keys.vault_enabled() {
+            "master key, Vault transit keys available"
+        } else {
+            "master key"
+        }
+    }
+
+    /// The owner of the key that seals data of `user_id`: the workspace of the document
+    /// or source `row` when it is in one, otherwise the workspace the running work is
+    /// done in, otherwise the user
+    pub async fn key_owner(&self, user_id: Uuid, row: Option<(WorkspaceScoped, Uuid)>) -> Result<KeyOwner> {
+        if let Some((scoped, id)) = row {
+            if let Some(owner) = self.db.get_key_owner(scoped, id).await? {
+                return Ok(owner);
+            }
+        }
+        Ok(KeyOwner::of(user_id, workspace_service::current()))
+    }
+
+    pub async fn encrypt_for(&self, owner: KeyOwner, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let key = self.active_key(owner).await?;
+        let data_key = self.data_key(&key).await?;
+        seal_blob(key.id, &data_key[..], plaintext)
+    }
+
+    /// Decrypt data sealed by any version of any key; other data is returned unchanged
+    pub async fn decrypt(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        let Some(key_id) = sealed_key_id(&data) else {
+            return Ok(data);
+        };
+        let key = self.db
+            .get_encryption_key(key_id)
+            .await?
+            .ok_or_else(|| anyhow!("Encryption key {} no longer exists", key_id))?;
+        let data_key = self.data_key(&key).await?;
+        open_blob(&data_key[..], &data)
+    }
+
+    /// Encrypt a column value, e.g. an OAuth token
+    pub async fn encrypt_field(&self, owner: KeyOwner, value: &str) -> Result<String> {
+        let sealed = self.encrypt_for(owner, value.as_bytes()).await?;
+        Ok(format!("{}{}", FIELD_PREFIX, Base64::encode_string(&sealed)))
+    }
+
+    /// Decrypt a column value written by `encrypt_field`; values stored before
+    /// encryption was enabled are returned unchanged
+    pub async fn decrypt_field(&self, value: &str) -> Result<String> {
+        let Some(encoded) = value.strip_prefix(FIELD_PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let sealed = Base64::decode_vec(encoded).map_err(|_| anyhow!("Encrypted value is not valid base64"))?;
+        let plaintext = self.decrypt(sealed).await?;
+        String::from_utf8(plaintext).map_err(|_| anyhow!("Encrypted value is not valid UTF-8"))
+    }
+
+    /// Seal the credentials of a source configuration that are not sealed yet
+    pub async fn seal_secrets(&self, owner: KeyOwner, config: &mut Value) -> Result<()> {
+        for secret in secret_values(config) {
+            if !secret.starts_with(FIELD_PREFIX) {
+                *secret = self.encrypt_field(owner, secret).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Open the credentials of a source configuration sealed by `seal_secrets`
+    pub async fn open_secrets(&self, config: &mut Value) -> Result<()> {
+        for secret in secret_values(config) {
+            *secret = self.decrypt_field(secret).await?;
+        }
+        Ok(())
+    }
+
+    /// The active key of a user or workspace, created wrapped by the master key on first use
+    pub async fn active_key(&self, owner: KeyOwner) -> Result<EncryptionKey> {
+        if let Some(key) = self.db.get_active_encryption_key(owner).await? {
+            return Ok(key);
+        }
+
+        let data_key = random_bytes::<KEY_LEN>();
+        let wrapped = self.keys.wrap(&KmsKeyRef::Master, &data_key).await?;
+        match self.db.create_initial_encryption_key(owner, KmsKeyRef::Master.provider(), None, &wrapped).await? {
+            Some(key) => {
+                self.data_keys.write().await.insert(key.id, Arc::new(data_key));
+                self.record_event(Some(key.id), owner, "created", None, Some("version 1 wrapped by the master key")).await;
+                Ok(key)
+            }
+            // Another request created the first key concurrently
+            None => self.db
+                .get_active_encryption_key(owner)
+                .await?
+                .ok_or_else(|| anyhow!("No active encryption key for {}", owner)),
+        }
+    }
+
+    /// Start a new key version wrapped by `kms_key_ref` (the master key when None) and
+    /// rewrap older versions with it too, so the previous KMS key can be revoked.
+    /// Data sealed with older versions stays readable.
+    pub async fn rotate(&self, owner: KeyOwner, kms_key_ref: Option<&str>, actor_user_id: Option<Uuid>) -> Result<EncryptionKey> {
+        let key_ref = KmsKeyRef::parse(kms_key_ref)?;
+        let ref_string = key_ref.to_ref_string();
+        let wrapped_by = ref_string.as_deref().unwrap_or("the master key");
+
+        // Unwrap and rewrap every older version first, so a KMS key that cannot be
+        // used fails the rotation before anything is changed
+        let mut rewrapped = Vec::new();
+        for key in self.db.get_encryption_keys(owner).await? {
+            if key.provider == key_ref.provider() && key.kms_key_ref == ref_string {
+                continue;
+            }
+            let data_key = self.data_key(&key).await?;
+            rewrapped.push((key.id, key.version, self.keys.wrap(&key_ref, &data_key[..]).await?));
+        }
+
+        let data_key = random_bytes::<KEY_LEN>();
+        let wrapped = self.keys.wrap(&key_ref, &data_key).await?;
+        let key = self.db
+            .rotate_encryption_key(owner, key_ref.provider(), ref_string.as_deref(), &wrapped)
+            .await?;
+        self.data_keys.write().await.insert(key.id, Arc::new(data_key));
+        let detail = format!("version {} wrapped by {}", key.version, wrapped_by);
+        self.record_event(Some(key.id), owner, "rotated", actor_user_id, Some(&detail)).await;
+
+        for (key_id, version, wrapped) in rewrapped {
+            self.db
+                .update_encryption_key_wrapping(key_id, key_ref.provider(), ref_string.as_deref(), &wrapped)
+                .await?;
+            let detail = format!("version {} rewrapped by {}", version, wrapped_by);
+            self.record_event(Some(key_id), owner, "rewrapped", actor_user_id, Some(&detail)).await;
+        }
+
+        Ok(key)
+    }
+
+    /// Audit entry; failures are logged rather than failing the operation
+    pub async fn record_event(
+        &self,
+        key_id: Option<Uuid>,
+        owner: KeyOwner,
+        action: &str,
+        actor_user_id: Option<Uuid>,
+        detail: Option<&str>,
+    ) {
+        if let Err(e) = self.db.record_encryption_key_event(key_id, owner, action, actor_user_id, detail).await {
+            warn!("Failed to record encryption key event '{}' for {}: {}", action, owner, e);
+        }
+    }
+
+    async fn data_key(&self, key: &EncryptionKey) -> Result<Arc<[u8; KEY_LEN]>> {
+        if let Some(data_key) = self.data_keys.read().await.get(&key.id) {
+            return Ok(data_key.clone());
+        }
+
+        let unwrapped = async {
+            let key_ref = KmsKeyRef::parse(key.kms_key_ref.as_deref())?;
+            let data_key: [u8; KEY_LEN] = self.keys
+                .unwrap(&key_ref, &key.wrapped_key)
+                .await?
+                .try_into()
+                .map_err(|_| anyhow!("Unwrapped key has the wrong length"))?;
+            Ok::<_, anyhow::Error>(data_key)
+        }
+        .await;
+
+        match unwrapped {
+            Ok(data_key) => {
+                let data_key = Arc::new(data_key);
+                self.data_keys.write().await.insert(key.id, data_key.clone());
+                self.record_event(Some(key.id), key.owner(), "unwrapped", None, None).await;
+                Ok(data_key)
+            }
+            Err(e) => {
+                let detail = e.to_string();
+                self.record_event(Some(key.id), key.owner(), "unwrap_failed", None, Some(&detail)).await;
+                Err(anyhow!("Failed to unwrap encryption key version {} of {}: {}", key.version, key.owner(), e))
+            }
+        }
+    }
+}
+
+/// Re-encrypt every document file and source credential sealed with the key of `owner`
+/// with its active version, e.g. after a rotation or to encrypt what was stored before
+/// encryption was enabled. Returns the number of rewritten and failed files and sources.
+pub async fn reencrypt_owner_data(db: &Database, file_service: &FileService, owner: KeyOwner) -> Result<(usize, usize)> {
+    let mut rewritten = 0;
+    let mut failed = 0;
+    for (document_id, user_id, filename, file_path) in db.get_key_owner_document_files(owner).await? {
+        match file_service.reencrypt_document_file(user_id, document_id, &filename, &file_path).await {
+            Ok(Some(new_path)) => {
+                if new_path != file_path {
+                    // Files stored before the storage layout change move to a new path;
+                    // the plaintext original must not be left behind
+                    db.set_document_file_path(document_id, &new_path).await?;
+                    // Shared content stays until its last reference is gone
+                    match file_service.release_blob(&file_path).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            if !file_path.starts_with("s3://") && !file_path.starts_with(AZURE_PATH_PREFIX) {
+                                if let Err(e) = tokio::fs::remove_file(&file_path).await {
+                                    warn!("Failed to remove {} after re-encrypting it to {}: {}", file_path, new_path, e);
+                                }
+                            }
+                        }
+                        Err(e) => warn!("Failed to release blob {} after re-encrypting it to {}: {}", file_path, new_path, e),
+                    }
+                }
+                rewritten += 1;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                failed += 1;
+                warn!("Failed to re-encrypt document {} of {}: {}", document_id, owner, e);
+            }
+        }
+    }
+
+    for source_id in db.get_key_owner_source_ids(owner).await? {
+        match db.reseal_source_secrets(source_id).await {
+            Ok(true) => rewritten += 1,
+            Ok(false) => {}
+            Err(e) => {
+                failed += 1;
+                warn!("Failed to re-encrypt the credentials of source {} of {}: {}", source_id, owner, e);
+            }
+        }
+    }
+
+    Ok((rewritten, failed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_roundtrip() {
+        let key_id = Uuid::new_v4();
+        let data_key = [3u8; KEY_LEN];
+        let sealed = seal_blob(key_id, &data_key, b"%PDF-1.7 invoice").unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert_eq!(sealed_key_id(&sealed), Some(key_id));
+        assert_eq!(open_blob(&data_key, &sealed).unwrap(), b"%PDF-1.7 invoice");
+        assert!(open_blob(&[4u8; KEY_LEN], &sealed).is_err());
+
+        // The key id in the header is authenticated
+        let mut tampered = sealed.clone();
+        tampered[MAGIC.len()] ^= 1;
+        assert!(open_blob(&data_key, &tampered).is_err());
+
+        assert!(!is_encrypted(b"%PDF-1.7 invoice"));
+        assert_eq!(sealed_key_id(b"RDENC1"), None);
+    }
+
+    #[test]
+    fn test_secret_values_of_source_configs() {
+        let mut config = serde_json::json!({
+            "server_url": "https://cloud.example.com",
+            "username": "alice",
+            "password": "hunter2",
+            "private_key": "",
+            "notifications": { "sqs_queue_url": null, "webhook_secret": "s3cret" },
+            "watch_folders": ["/Documents"],
+        });
+        let mut secrets: Vec<String> = secret_values(&mut config).into_iter().map(|secret| secret.clone()).collect();
+        secrets.sort();
+        assert_eq!(secrets, ["hunter2", "s3cret"]);
+
+        let key_id = Uuid::new_v4();
+        assert!(!secrets_sealed_with(&config, key_id));
+        let sealed = seal_blob(key_id, &[3u8; KEY_LEN], b"hunter2").unwrap();
+        for secret in secret_values(&mut config) {
+            *secret = format!("{}{}", FIELD_PREFIX, Base64::encode_string(&sealed));
+        }
+        assert!(secrets_sealed_with(&config, key_id));
+        assert!(!secrets_sealed_with(&config, Uuid::new_v4()));
+        assert_eq!(config["username"], "alice");
+    }
+}
@@ -0,0 +1,212 @@
+//! Entity pages: everything a user's documents say about one entity of the knowledge
+//! graph, gathered from the latest graph of each document with corrections applied.
+
+use anyhow::Result;
+use chrono::Datelike;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{
+    normalize_entity_name, DocumentGraph, EntityAttribute, EntityAttributeValue, EntityMention, EntityProfile,
+    EntityRelationship, EntityTimelineBucket, GraphEntity, RelationshipDirection, MAX_ENTITY_PROFILE_DOCUMENTS,
+};
+
+pub struct EntityService {
+    db: Database,
+}
+
+impl EntityService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    pub async fn profile(&self, entity: GraphEntity) -> Result<EntityProfile> {
+        let mut candidates = self.db.find_entity_documents(&entity, MAX_ENTITY_PROFILE_DOCUMENTS + 1).await?;
+        let truncated = candidates.len() as i64 > MAX_ENTITY_PROFILE_DOCUMENTS;
+        candidates.truncate(MAX_ENTITY_PROFILE_DOCUMENTS as usize);
+
+        let mut builder = ProfileBuilder::default();
+        for mention in candidates {
+            let graph = self.db.get_document_graph(mention.document_id, None).await?;
+            builder.add(&entity, mention, &graph);
+        }
+
+        let others: Vec<(String, String)> = builder.relationships.keys().map(|key| (key.2.clone(), key.3.clone())).collect();
+        let entity_ids = self.db.find_graph_entity_ids(entity.user_id, &others).await?;
+        Ok(builder.finish(entity, &entity_ids, truncated))
+    }
+}
+
+/// (direction, relationship, other label, other normalized name)
+type RelationshipKey = (RelationshipDirection, String, String, String);
+
+#[derive(Default)]
+struct ProfileBuilder {
+    documents: Vec<EntityMention>,
+    /// Display name of the other entity and the documents, per relationship
+    relationships: BTreeMap<RelationshipKey, (String, Vec<Uuid>)>,
+    /// Values with their documents, per attribute
+    attributes: BTreeMap<String, Vec<EntityAttributeValue>>,
+}
+
+impl ProfileBuilder {
+    /// Add what the document's graph says about the entity; documents whose corrected
+    /// graph no longer names it are left out
+    fn add(&mut self, entity: &GraphEntity, mention: EntityMention, graph: &DocumentGraph) {
+        let nodes: Vec<_> = graph.nodes.iter().filter(|node| entity.matches(&node.label, &node.name)).collect();
+        if nodes.is_empty() {
+            return;
+        }
+        let document_id = mention.document_id;
+        self.documents.push(mention);
+
+        for node in &nodes {
+            let Some(Value::Object(properties)) = &node.properties else {
+                continue;
+            };
+            for (key, value) in properties {
+                let values = self.attributes.entry(key.clone()).or_default();
+                match values.iter_mut().find(|existing| existing.value == *value) {
+                    Some(existing) if !existing.document_ids.contains(&document_id) => existing.document_ids.push(document_id),
+                    Some(_) => {}
+                    None => values.push(EntityAttributeValue { value: value.clone(), document_ids: vec![document_id] }),
+                }
+            }
+        }
+
+        let node_ids: Vec<Uuid> = nodes.iter().map(|node| node.id).collect();
+        for edge in &graph.edges {
+            let (direction, other_id) = match (node_ids.contains(&edge.source), node_ids.contains(&edge.target)) {
+                (true, false) => (RelationshipDirection::Outgoing, edge.target),
+                (false, true) => (RelationshipDirection::Incoming, edge.source),
+                _ => continue,
+            };
+            let Some(other) = graph.nodes.iter().find(|node| node.id == other_id) else {
+                continue;
+            };
+            let key = (direction, edge.relationship.clone(), other.label.clone(), normalize_entity_name(&other.name));
+            let (_, documents) = self.relationships.entry(key).or_insert_with(|| (other.name.trim().to_string(), Vec::new()));
+            if !documents.contains(&document_id) {
+                documents.push(document_id);
+            }
+        }
+    }
+
+    fn finish(
+        mut self,
+        entity: GraphEntity,
+        entity_ids: &HashMap<(String, String), Uuid>,
+        truncated: bool,
+    ) -> EntityProfile {
+        self.documents.sort_by(|a, b| a.document_date.cmp(&b.document_date).then(a.document_id.cmp(&b.document_id)));
+
+        let mut timeline: Vec<EntityTimelineBucket> = Vec::new();
+        for mention in &self.documents {
+            let period = format!("{:04}-{:02}", mention.document_date.year(), mention.document_date.month());
+            match timeline.last_mut() {
+                Some(bucket) if bucket.period == period => bucket.mentions += 1,
+                _ => timeline.push(EntityTimelineBucket { period, mentions: 1 }),
+            }
+        }
+
+        let mut relationships: Vec<EntityRelationship> = self
+            .relationships
+            .into_iter()
+            .map(|((direction, relationship, label, normalized_name), (name, document_ids))| EntityRelationship {
+                entity_id: entity_ids.get(&(label.clone(), normalized_name)).copied(),
+                direction,
+                relationship,
+                label,
+                name,
+                document_ids,
+            })
+            .collect();
+        relationships.sort_by(|a, b| b.document_ids.len().cmp(&a.document_ids.len()));
+
+        EntityProfile {
+            entity,
+            documents: self.documents,
+            relationships,
+            attributes: self.attributes.into_iter().map(|(key, values)| EntityAttribute { key, values }).collect(),
+            timeline,
+            truncated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DocumentGraphEdge, DocumentGraphNode};
+    use serde_json::json;
+
+    fn entity() -> GraphEntity {
+        GraphEntity {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            label: "Person".to_string(),
+            name: "John Doe".to_string(),
+            normalized_name: "john doe".to_string(),
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn node(label: &str, name: &str, properties: Value) -> DocumentGraphNode {
+        DocumentGraphNode { id: Uuid::new_v4(), label: label.to_string(), name: name.to_string(), properties: Some(properties) }
+    }
+
+    fn document(date: &str, john: &DocumentGraphNode, acme: &DocumentGraphNode) -> (EntityMention, DocumentGraph) {
+        let mention = EntityMention {
+            document_id: Uuid::new_v4(),
+            filename: "letter.pdf".to_string(),
+            document_date: date.parse().unwrap(),
+        };
+        let graph = DocumentGraph {
+            version: None,
+            nodes: vec![john.clone(), acme.clone()],
+            edges: vec![DocumentGraphEdge {
+                id: Uuid::new_v4(),
+                source: john.id,
+                target: acme.id,
+                relationship: "WORKS_FOR".to_string(),
+                properties: None,
+            }],
+            corrections_applied: 0,
+        };
+        (mention, graph)
+    }
+
+    #[test]
+    fn test_profile_merges_documents() {
+        let entity = entity();
+        let acme = node("Company", "ACME", json!({}));
+        let mut builder = ProfileBuilder::default();
+
+        let (march, march_graph) = document("2024-03-20T00:00:00Z", &node("Person", "john doe ", json!({"role": "Engineer"})), &acme);
+        let (january, january_graph) = document("2024-01-05T00:00:00Z", &node("Person", "John Doe", json!({"role": "Engineer"})), &acme);
+        let (other, other_graph) = document("2024-03-01T00:00:00Z", &node("Person", "Jane Roe", json!({})), &acme);
+        builder.add(&entity, march, &march_graph);
+        builder.add(&entity, january.clone(), &january_graph);
+        builder.add(&entity, other, &other_graph);
+
+        let acme_id = Uuid::new_v4();
+        let ids = HashMap::from([(("Company".to_string(), "acme".to_string()), acme_id)]);
+        let profile = builder.finish(entity, &ids, false);
+
+        assert_eq!(profile.documents.len(), 2);
+        assert_eq!(profile.documents[0].document_id, january.document_id);
+        assert_eq!(profile.timeline.iter().map(|b| (b.period.as_str(), b.mentions)).collect::<Vec<_>>(), vec![
+            ("2024-01", 1),
+            ("2024-03", 1)
+        ]);
+        assert_eq!(profile.relationships.len(), 1);
+        assert_eq!(profile.relationships[0].direction, RelationshipDirection::Outgoing);
+        assert_eq!(profile.relationships[0].entity_id, Some(acme_id));
+        assert_eq!(profile.relationships[0].document_ids.len(), 2);
+        assert_eq!(profile.attributes.len(), 1);
+        assert_eq!(profile.attributes[0].values.len(), 1);
+        assert_eq!(profile.attributes[0].values[0].document_ids.len(), 2);
+    }
+}
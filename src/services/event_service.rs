@@ -0,0 +1,773 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use reqwest::Client;
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{
+    Document, EventEnvelope, EventType, ReplayEventsResponse, WebhookDelivery, WebhookDeliveryStatus,
+    WebhookEndpoint, WebhookEventContext,
+};
+
+pub const EVENT_ID_HEADER: &str = "X-Readur-Event-Id";
+pub const EVENT_TYPE_HEADER: &str = "X-Readur-Event-Type";
+pub const SCHEMA_VERSION_HEADER: &str = "X-Readur-Schema-Version";
+pub const DELIVERY_ID_HEADER: &str = "X-Readur-Delivery-Id";
+pub const TIMESTAMP_HEADER: &str = "X-Readur-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Readur-Signature";
+
+/// Shortest signing secret accepted from users
+pub const MIN_SECRET_LENGTH: usize = 16;
+const SECRET_BYTES: usize = 32;
+
+/// Attempts per delivery before it is given up, unless `WEBHOOK_MAX_ATTEMPTS` is set
+const DEFAULT_MAX_ATTEMPTS: i32 = 8;
+/// Wait before the first retry; it doubles with every further attempt
+const RETRY_BASE_SECONDS: i64 = 30;
+const MAX_RETRY_DELAY_SECONDS: i64 = 6 * 60 * 60;
+/// How often due retries are looked for
+const RETRY_POLL_SECONDS: u64 = 30;
+const RETRY_BATCH_SIZE: i64 = 50;
+/// Events sent per replay request at most
+const REPLAY_BATCH_SIZE: i64 = 100;
+/// A replay request stops starting deliveries after this long and leaves the rest for
+/// the next batch
+const REPLAY_TIME_BUDGET: Duration = Duration::from_secs(60);
+/// Days stored events are kept, unless `EVENT_RETENTION_DAYS` is set
+const DEFAULT_EVENT_RETENTION_DAYS: i64 = 90;
+/// Pause between removals of events past the retention period
+const EVENT_CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// A new random signing secret, hex encoded
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `X-Readur-Signature` of a delivery: `sha256=` and the hex HMAC-SHA256 of
+/// `<timestamp>.<body>` keyed with the endpoint's secret
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let signature: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", signature)
+}
+
+/// Wait before the next attempt after `attempts` failed ones: 30 seconds, then
+/// doubling up to six hours
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    chrono::Duration::seconds((RETRY_BASE_SECONDS << exponent).min(MAX_RETRY_DELAY_SECONDS))
+}
+
+fn event_retention_days() -> i64 {
+    std::env::var("EVENT_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_EVENT_RETENTION_DAYS)
+}
+
+fn max_attempts() -> i32 {
+    std::env::var("WEBHOOK_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_ATTEMPTS)
+        .max(1)
+}
+
+/// Events a slow subscriber may fall behind by before it misses some
+const EVENT_BUS_CAPACITY: usize = 1024;
+/// How often OCR queue counts are checked for changes while clients are connected
+const QUEUE_PROGRESS_POLL_SECONDS: u64 = 2;
+
+/// Every event published in this process, for the WebSocket event stream
+static EVENT_BUS: Lazy<broadcast::Sender<EventEnvelope>> = Lazy::new(|| broadcast::channel(EVENT_BUS_CAPACITY).0);
+
+/// Receive every event published from now on. A receiver that falls more than
+/// 1024 events behind gets `RecvError::Lagged` and has to catch up from the log.
+pub fn subscribe() -> broadcast::Receiver<EventEnvelope> {
+    EVENT_BUS.subscribe()
+}
+
+/// Outcome of sending a delivery once
+struct DeliveryAttempt {
+    response_status: Option<i32>,
+    error: Option<String>,
+}
+
+/// Records versioned events and delivers them to HTTP targets
+#[derive(Clone)]
+pub struct EventService {
+    db: Database,
+    client: Client,
+}
+
+impl EventService {
+    pub fn new(db: Database) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
+        Self { db, client }
+    }
+
+    /// Persist an event so it can be delivered and later replayed, and send it to
+    /// stream subscribers. Types that are not stored are only broadcast.
+    pub async fn publish(
+        &self,
+        event_type: EventType,
+        user_id: Option<Uuid>,
+        resource_id: Option<Uuid>,
+        data: serde_json::Value,
+    ) -> Result<EventEnvelope> {
+        if !event_type.is_stored() {
+            return Ok(broadcast_event(event_type, user_id, resource_id, data));
+        }
+
+        let stored = self.db.create_event(event_type, user_id, resource_id, &data).await?;
+        debug!("Recorded event {} ({})", stored.id, event_type);
+        let envelope: EventEnvelope = stored.into();
+        // Sending only fails when nobody is subscribed
+        let _ = EVENT_BUS.send(envelope.clone());
+
+        // Webhook endpoints belong to users, so ownerless events have nowhere to go
+        if envelope.user_id.is_some() {
+            let service = self.clone();
+            let event = envelope.clone();
+            crate::errors::panic::spawn_guarded(format!("Webhook delivery of event {}", event.event_id), async move {
+                if let Err(e) = service.deliver_to_endpoints(&event).await {
+                    warn!("Failed to deliver event {} to webhook endpoints: {}", event.event_id, e);
+                }
+            });
+        }
+
+        Ok(envelope)
+    }
+
+    /// Publish an event, logging instead of failing. Event recording must never
+    /// break the operation that triggered it.
+    pub async fn publish_best_effort(
+        &self,
+        event_type: EventType,
+        user_id: Option<Uuid>,
+        resource_id: Option<Uuid>,
+        data: serde_json::Value,
+    ) {
+        if let Err(e) = self.publish(event_type, user_id, resource_id, data).await {
+            warn!("Failed to record {} event: {}", event_type, e);
+        }
+    }
+
+    /// Publish `document.updated` for the fields of a document that changed
+    pub async fn publish_document_updated(&self, document: &Document, fields: &[&str]) {
+        self.publish_best_effort(
+            EventType::DocumentUpdated,
+            Some(document.user_id),
+            Some(document.id),
+            json!({
+                "document_id": document.id,
+                "filename": document.filename,
+                "fields": fields,
+            }),
+        )
+        .await;
+    }
+
+    /// Publish `document.labels_changed` with the labels the document has now
+    pub async fn publish_labels_changed(&self, document_id: Uuid) {
+        let (owner_id, label_ids) = match self.db.get_document_owner_and_label_ids(document_id).await {
+            Ok(Some(labels)) => labels,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to load labels of document {} for its event: {}", document_id, e);
+                return;
+            }
+        };
+
+        self.publish_best_effort(
+            EventType::DocumentLabelsChanged,
+            Some(owner_id),
+            Some(document_id),
+            json!({
+                "document_id": document_id,
+                "label_ids": label_ids,
+            }),
+        )
+        .await;
+    }
+
+    /// Broadcast `queue.progress` to every user whose OCR queue counts changed, while
+    /// anyone is subscribed to the event stream
+    pub async fn run_queue_progress(self) {
+        let mut last_counts: HashMap<Uuid, (i64, i64)> = HashMap::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(QUEUE_PROGRESS_POLL_SECONDS));
+        loop {
+            interval.tick().await;
+            if EVENT_BUS.receiver_count() == 0 {
+                last_counts.clear();
+                continue;
+            }
+
+            let counts: HashMap<Uuid, (i64, i64)> = match self.db.get_ocr_queue_counts_by_user().await {
+                Ok(counts) => counts
+                    .into_iter()
+                    .map(|(user_id, pending, processing)| (user_id, (pending, processing)))
+                    .collect(),
+                Err(e) => {
+                    warn!("Failed to get OCR queue counts: {}", e);
+                    continue;
+                }
+            };
+
+            // Users whose queue emptied get a last event with zero counts
+            let drained = last_counts
+                .keys()
+                .filter(|user_id| !counts.contains_key(*user_id))
+                .map(|user_id| (*user_id, (0, 0)));
+            let changed: Vec<(Uuid, (i64, i64))> = counts
+                .iter()
+                .filter(|&(user_id, current)| last_counts.get(user_id) != Some(current))
+                .map(|(user_id, current)| (*user_id, *current))
+                .chain(drained)
+                .collect();
+
+            for (user_id, (pending, processing)) in changed {
+                broadcast_event(
+                    EventType::QueueProgress,
+                    Some(user_id),
+                    None,
+                    json!({ "pending": pending, "processing": processing }),
+                );
+            }
+            last_counts = counts;
+        }
+    }
+
+    /// POST a single envelope to `target_url`
+    pub async fn deliver(&self, target_url: &str, envelope: &EventEnvelope) -> Result<()> {
+        let response = self.client
+            .post(target_url)
+            .header(EVENT_ID_HEADER, envelope.event_id.to_string())
+            .header(EVENT_TYPE_HEADER, envelope.event_type.as_str())
+            .header(SCHEMA_VERSION_HEADER, envelope.schema_version.to_string())
+            .json(envelope)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Target {} responded with {} for event {}",
+                target_url, response.status(), envelope.event_id
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Deliver an event to every enabled endpoint of its owner whose filter matches,
+    /// logging each delivery; failed ones are retried later by [`Self::run_retries`].
+    /// Returns how many endpoints accepted it.
+    pub async fn deliver_to_endpoints(&self, envelope: &EventEnvelope) -> Result<usize> {
+        let Some(user_id) = envelope.user_id else {
+            return Ok(0);
+        };
+
+        let endpoints = self.db.get_enabled_webhook_endpoints(user_id).await?;
+        if endpoints.is_empty() {
+            return Ok(0);
+        }
+
+        let context = self.event_context(envelope).await?;
+        let mut delivered = 0;
+
+        for endpoint in endpoints
+            .iter()
+            .filter(|endpoint| endpoint.filter.matches(envelope.event_type, &context))
+        {
+            let Some(delivery) = self.db.create_webhook_delivery(endpoint.id, envelope).await? else {
+                continue;
+            };
+            if self.attempt_delivery(endpoint, &delivery, envelope).await {
+                delivered += 1;
+            }
+        }
+
+        Ok(delivered)
+    }
+
+    /// Retry due deliveries every 30 seconds
+    pub async fn run_retries(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(RETRY_POLL_SECONDS));
+        loop {
+            interval.tick().await;
+            match self.retry_due_deliveries().await {
+                Ok(0) => {}
+                Ok(retried) => info!("Retried {} webhook deliveries", retried),
+                Err(e) => warn!("Failed to retry webhook deliveries: {}", e),
+            }
+        }
+    }
+
+    /// Attempt the pending deliveries whose retry is due. Returns how many were attempted.
+    pub async fn retry_due_deliveries(&self) -> Result<usize> {
+        let deliveries = self.db.claim_due_webhook_deliveries(RETRY_BATCH_SIZE).await?;
+        let attempted = deliveries.len();
+
+        for delivery in deliveries {
+            let endpoint = self.db.get_webhook_endpoint_by_id(delivery.endpoint_id).await?;
+            let event = self.db.get_event(delivery.event_id).await?;
+            match (endpoint, event) {
+                (Some(endpoint), Some(event)) if endpoint.enabled => {
+                    let envelope: EventEnvelope = event.into();
+                    self.attempt_delivery(&endpoint, &delivery, &envelope).await;
+                }
+                (Some(_), Some(_)) => {
+                    self.db
+                        .record_webhook_delivery_attempt(
+                            delivery.id,
+                            WebhookDeliveryStatus::Failed,
+                            None,
+                            Some("Endpoint is disabled"),
+                            None,
+                        )
+                        .await?;
+                }
+                // The endpoint or event is gone and its deliveries with it
+                _ => {}
+            }
+        }
+
+        Ok(attempted)
+    }
+
+    /// Send a logged delivery once and record the outcome, scheduling a retry when it
+    /// failed and attempts are left. Returns whether the endpoint accepted it.
+    async fn attempt_delivery(&self, endpoint: &WebhookEndpoint, delivery: &WebhookDelivery, envelope: &EventEnvelope) -> bool {
+        let attempt = self.send_signed(endpoint, delivery.id, envelope).await;
+        let attempts = delivery.attempts + 1;
+
+        let (status, next_attempt_at) = match &attempt.error {
+            None => (WebhookDeliveryStatus::Succeeded, None),
+            Some(error) if attempts < max_attempts() => {
+                let delay = retry_delay(attempts);
+                warn!(
+                    "Webhook endpoint {} did not accept event {} (attempt {}), retrying in {}s: {}",
+                    endpoint.id, envelope.event_id, attempts, delay.num_seconds(), error
+                );
+                (WebhookDeliveryStatus::Pending, Some(Utc::now() + delay))
+            }
+            Some(error) => {
+                warn!(
+                    "Webhook endpoint {} did not accept event {} after {} attempts: {}",
+                    endpoint.id, envelope.event_id, attempts, error
+                );
+                (WebhookDeliveryStatus::Failed, None)
+            }
+        };
+
+        if let Err(e) = self
+            .db
+            .record_webhook_delivery_attempt(delivery.id, status, attempt.response_status, attempt.error.as_deref(), next_attempt_at)
+            .await
+        {
+            warn!("Failed to record webhook delivery {}: {}", delivery.id, e);
+        }
+        if let Err(e) = self.db.record_webhook_delivery(endpoint.id, attempt.error.as_deref()).await {
+            warn!("Failed to record delivery to webhook endpoint {}: {}", endpoint.id, e);
+        }
+
+        attempt.error.is_none()
+    }
+
+    /// POST an envelope to an endpoint, signed with its secret
+    async fn send_signed(&self, endpoint: &WebhookEndpoint, delivery_id: Uuid, envelope: &EventEnvelope) -> DeliveryAttempt {
+        let body = match serde_json::to_vec(envelope) {
+            Ok(body) => body,
+            Err(e) => return DeliveryAttempt { response_status: None, error: Some(e.to_string()) },
+        };
+        let timestamp = Utc::now().timestamp();
+
+        let response = self.client
+            .post(&endpoint.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_ID_HEADER, envelope.event_id.to_string())
+            .header(EVENT_TYPE_HEADER, envelope.event_type.as_str())
+            .header(SCHEMA_VERSION_HEADER, envelope.schema_version.to_string())
+            .header(DELIVERY_ID_HEADER, delivery_id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, sign_payload(&endpoint.secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => DeliveryAttempt {
+                response_status: Some(response.status().as_u16() as i32),
+                error: None,
+            },
+            Ok(response) => DeliveryAttempt {
+                response_status: Some(response.status().as_u16() as i32),
+                error: Some(format!("Endpoint responded with {}", response.status())),
+            },
+            Err(e) => DeliveryAttempt { response_status: None, error: Some(e.to_string()) },
+        }
+    }
+
+    /// Attributes webhook filters are evaluated against: the current state of the
+    /// document the event is about, else whatever the payload carries
+    async fn event_context(&self, envelope: &EventEnvelope) -> Result<WebhookEventContext> {
+        let uuid_field = |name: &str| {
+            envelope.data.get(name)
+                .and_then(|value| value.as_str())
+                .and_then(|value| Uuid::parse_str(value).ok())
+        };
+
+        if let Some(document_id) = uuid_field("document_id") {
+            if let Some(context) = self.db.get_webhook_document_context(document_id).await? {
+                return Ok(context);
+            }
+        }
+
+        Ok(WebhookEventContext {
+            tags: Vec::new(),
+            source_id: uuid_field("source_id"),
+            mime_type: envelope.data.get("mime_type")
+                .and_then(|value| value.as_str())
+                .map(str::to_string),
+        })
+    }
+
+    /// Re-deliver the next batch of stored events in `[from, to)` to `target_url`, in
+    /// order, starting after the `(occurred_at, id)` position when one is given. Events
+    /// keep their original id so consumers can deduplicate.
+    pub async fn replay(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        event_types: Option<&[EventType]>,
+        target_url: &str,
+    ) -> Result<ReplayEventsResponse> {
+        // One more than a batch tells whether any remain
+        let mut events = self.db.get_events_in_range(from, to, after, event_types, REPLAY_BATCH_SIZE + 1).await?;
+        let mut more = events.len() as i64 > REPLAY_BATCH_SIZE;
+        events.truncate(REPLAY_BATCH_SIZE as usize);
+
+        let started = std::time::Instant::now();
+        let mut total = 0;
+        let mut last_event_id = None;
+        let mut failed_event_ids = Vec::new();
+        for event in events {
+            if started.elapsed() >= REPLAY_TIME_BUDGET {
+                more = true;
+                break;
+            }
+            let envelope: EventEnvelope = event.into();
+            total += 1;
+            last_event_id = Some(envelope.event_id);
+            if let Err(e) = self.deliver(target_url, &envelope).await {
+                warn!("Replay delivery of event {} failed: {}", envelope.event_id, e);
+                failed_event_ids.push(envelope.event_id);
+            }
+        }
+
+        Ok(ReplayEventsResponse {
+            total,
+            delivered: total - failed_event_ids.len(),
+            failed: failed_event_ids.len(),
+            failed_event_ids,
+            next_after_event_id: if more { last_event_id.or(after.map(|(_, id)| id)) } else { None },
+        })
+    }
+
+    /// Remove stored events past `EVENT_RETENTION_DAYS` every hour
+    pub async fn run_cleanup(self) {
+        let mut interval = tokio::time::interval(EVENT_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.db.prune_events(event_retention_days()).await {
+                Ok(0) => {}
+                Ok(removed) => info!("Removed {} event(s) past retention", removed),
+                Err(e) => warn!("Failed to remove old events: {}", e),
+            }
+        }
+    }
+}
+
+/// Send an event to stream subscribers without storing it
+pub(crate) fn broadcast_event(
+    event_type: EventType,
+    user_id: Option<Uuid>,
+    resource_id: Option<Uuid>,
+    data: serde_json::Value,
+) -> EventEnvelope {
+    let envelope = EventEnvelope {
+        event_id: Uuid::new_v4(),
+        event_type,
+        schema_version: event_type.schema_version(),
+        occurred_at: Utc::now(),
+        user_id,
+        resource_id,
+        data,
+    };
+    let _ = EVENT_BUS.send(envelope.clone());
+    envelope
+}
+
+/// JSON Schema for the `data` object of an event type at its current version
+pub fn data_schema(event_type: EventType) -> serde_json::Value {
+    let uuid = json!({"type": "string", "format": "uuid"});
+    let timestamp = json!({"type": "string", "format": "date-time"});
+
+    match event_type {
+        EventType::DocumentCreated => json!({
+            "type": "object",
+            "required": ["document_id", "filename", "mime_type", "file_size"],
+            "properties": {
+                "document_id": uuid,
+                "filename": {"type": "string"},
+                "mime_type": {"type": "string"},
+                "file_size": {"type": "integer"},
+                "source_type": {"type": ["string", "null"]},
+                "source_id": {"type": ["string", "null"], "format": "uuid"}
+            }
+        }),
+        EventType::DocumentDeleted => json!({
+            "type": "object",
+            "required": ["document_id", "filename"],
+            "properties": {
+                "document_id": uuid,
+                "filename": {"type": "string"}
+            }
+        }),
+        EventType::DocumentUpdated => json!({
+            "type": "object",
+            "required": ["document_id", "filename", "fields"],
+            "properties": {
+                "document_id": uuid,
+                "filename": {"type": "string"},
+                "fields": {
+                    "type": "array",
+                    "items": {"enum": ["filename", "file", "custom_fields", "ocr_text"]}
+                }
+            }
+        }),
+        EventType::DocumentLabelsChanged => json!({
+            "type": "object",
+            "required": ["document_id", "label_ids"],
+            "properties": {
+                "document_id": uuid,
+                "label_ids": {"type": "array", "items": uuid}
+            }
+        }),
+        EventType::OcrCompleted => json!({
+            "type": "object",
+            "required": ["document_id", "confidence", "word_count", "processing_time_ms"],
+            "properties": {
+                "document_id": uuid,
+                "confidence": {"type": "number"},
+                "word_count": {"type": "integer"},
+                "processing_time_ms": {"type": "integer"}
+            }
+        }),
+        EventType::OcrFailed => json!({
+            "type": "object",
+            "required": ["document_id", "failure_reason", "error"],
+            "properties": {
+                "document_id": uuid,
+                "failure_reason": {"type": "string"},
+                "error": {"type": "string"}
+            }
+        }),
+        EventType::AnalysisCompleted => json!({
+            "type": "object",
+            "required": ["document_id", "node_count", "edge_count"],
+            "properties": {
+                "document_id": uuid,
+                "node_count": {"type": "integer"},
+                "edge_count": {"type": "integer"}
+            }
+        }),
+        EventType::SyncCompleted => json!({
+            "type": "object",
+            "required": ["source_id", "source_type", "files_processed", "finished_at"],
+            "properties": {
+                "source_id": uuid,
+                "source_type": {"type": "string"},
+                "files_processed": {"type": "integer"},
+                "finished_at": timestamp
+            }
+        }),
+        EventType::SyncFailed => json!({
+            "type": "object",
+            "required": ["source_id", "source_type", "error"],
+            "properties": {
+                "source_id": uuid,
+                "source_type": {"type": "string"},
+                "error": {"type": "string"}
+            }
+        }),
+        EventType::QueueProgress => json!({
+            "type": "object",
+            "required": ["pending", "processing"],
+            "properties": {
+                "pending": {"type": "integer"},
+                "processing": {"type": "integer"}
+            }
+        }),
+        EventType::DocumentProgress => json!({
+            "type": "object",
+            "required": ["document_id", "stage", "status", "percent", "overall_percent", "updated_at"],
+            "properties": {
+                "document_id": uuid,
+                "stage": {"type": "string", "enum": ["ingestion", "ocr", "analysis"]},
+                "status": {"type": "string", "enum": ["queued", "running", "completed", "failed"]},
+                "percent": {"type": "integer", "minimum": 0, "maximum": 100},
+                "overall_percent": {"type": "integer", "minimum": 0, "maximum": 100},
+                "updated_at": timestamp
+            }
+        }),
+    }
+}
+
+/// JSON Schema for the full delivery envelope of an event type
+pub fn envelope_schema(event_type: EventType) -> serde_json::Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("https://readur.app/schemas/events/{}/v{}.json", event_type.as_str(), event_type.schema_version()),
+        "title": event_type.as_str(),
+        "type": "object",
+        "required": ["event_id", "event_type", "schema_version", "occurred_at", "data"],
+        "properties": {
+            "event_id": {"type": "string", "format": "uuid"},
+            "event_type": {"const": event_type.as_str()},
+            "schema_version": {"const": event_type.schema_version()},
+            "occurred_at": {"type": "string", "format": "date-time"},
+            "user_id": {"type": ["string", "null"], "format": "uuid"},
+            "resource_id": {"type": ["string", "null"], "format": "uuid"},
+            "data": data_schema(event_type)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EventStreamQuery, WebhookEventFilter};
+
+    #[test]
+    fn test_event_type_round_trip() {
+        for event_type in EventType::ALL {
+            let parsed = EventType::try_from(event_type.as_str().to_string()).unwrap();
+            assert_eq!(parsed, event_type);
+
+            let serialized = serde_json::to_value(event_type).unwrap();
+            assert_eq!(serialized, json!(event_type.as_str()));
+        }
+        assert!(EventType::try_from("document.renamed".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_webhook_filter_matching() {
+        let source_id = Uuid::new_v4();
+        let invoice = WebhookEventContext {
+            tags: vec!["Invoices".to_string()],
+            source_id: Some(source_id),
+            mime_type: Some("application/pdf".to_string()),
+        };
+        let sync = WebhookEventContext { source_id: Some(source_id), ..Default::default() };
+
+        let everything = WebhookEventFilter::default();
+        assert!(everything.matches(EventType::OcrCompleted, &invoice));
+        assert!(everything.matches(EventType::SyncFailed, &sync));
+
+        let filter = WebhookEventFilter {
+            event_types: vec![EventType::DocumentCreated, EventType::OcrCompleted],
+            tags: vec!["invoices".to_string(), "receipts".to_string()],
+            source_ids: vec![source_id],
+            mime_types: vec!["application/pdf".to_string()],
+        };
+        assert!(filter.matches(EventType::OcrCompleted, &invoice));
+        assert!(!filter.matches(EventType::OcrFailed, &invoice));
+        assert!(!filter.matches(EventType::DocumentCreated, &sync));
+
+        let other_source = WebhookEventContext { source_id: Some(Uuid::new_v4()), ..invoice.clone() };
+        assert!(!filter.matches(EventType::OcrCompleted, &other_source));
+
+        let images = WebhookEventFilter { mime_types: vec!["image/*".to_string()], ..Default::default() };
+        let scan = WebhookEventContext { mime_type: Some("image/tiff".to_string()), ..Default::default() };
+        assert!(images.matches(EventType::DocumentCreated, &scan));
+        assert!(!images.matches(EventType::DocumentCreated, &invoice));
+        assert!(!images.matches(EventType::SyncCompleted, &sync));
+    }
+
+    #[test]
+    fn test_sign_payload() {
+        // HMAC-SHA256 of "1700000000.{}" keyed with "secret"
+        let signature = sign_payload("secret", 1_700_000_000, b"{}");
+        assert_eq!(signature, "sha256=b8569b78799ff9e3cbff0fc2d63a33a2b57f3282abd07c37ae5e8e7d79a5f163");
+        assert_ne!(signature, sign_payload("secret", 1_700_000_001, b"{}"));
+        assert_ne!(signature, sign_payload("other secret", 1_700_000_000, b"{}"));
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1).num_seconds(), 30);
+        assert_eq!(retry_delay(2).num_seconds(), 60);
+        assert_eq!(retry_delay(4).num_seconds(), 240);
+        assert_eq!(retry_delay(30).num_seconds(), 6 * 60 * 60);
+        assert_eq!(retry_delay(0).num_seconds(), 30);
+    }
+
+    #[test]
+    fn test_transient_events_reach_subscribers() {
+        let mut events = subscribe();
+        let user_id = Uuid::new_v4();
+        let sent = broadcast_event(EventType::QueueProgress, Some(user_id), None, json!({"pending": 2, "processing": 1}));
+
+        let received = std::iter::from_fn(|| events.try_recv().ok())
+            .find(|envelope| envelope.event_id == sent.event_id)
+            .expect("event was broadcast");
+        assert_eq!(received.user_id, Some(user_id));
+        assert!(!received.event_type.is_stored());
+    }
+
+    #[test]
+    fn test_stream_query_event_types() {
+        let query = EventStreamQuery {
+            last_event_id: None,
+            event_types: Some("document.updated, queue.progress,".to_string()),
+        };
+        assert_eq!(
+            query.parse_event_types().unwrap(),
+            Some(vec![EventType::DocumentUpdated, EventType::QueueProgress])
+        );
+
+        let unknown = EventStreamQuery { last_event_id: None, event_types: Some("document.renamed".to_string()) };
+        assert!(unknown.parse_event_types().is_err());
+    }
+
+    #[test]
+    fn test_envelope_schema_pins_type_and_version() {
+        for event_type in EventType::ALL {
+            let schema = envelope_schema(event_type);
+            assert_eq!(schema["properties"]["event_type"]["const"], json!(event_type.as_str()));
+            assert_eq!(schema["properties"]["schema_version"]["const"], json!(event_type.schema_version()));
+            assert_eq!(schema["properties"]["data"]["type"], json!("object"));
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::Result;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+use crate::db::Database;
+use crate::models::{CreateNotification, Document, DocumentExpiration, EXPIRATION_KIND_RENEWAL, MAX_NOTICE_DAYS};
+use crate::ocr::expiration_dates;
+use crate::services::i18n;
+
+/// How often expirations entering their notice period are looked for
+const POLL_SECONDS: u64 = 3600;
+/// Notifications sent per run; the rest wait for the next one
+const BATCH_SIZE: i64 = 200;
+
+/// Whether to look for expiration and renewal dates in the OCR text of new documents
+static ENABLED_AFTER_OCR: Lazy<bool> = Lazy::new(|| {
+    std::env::var("EXPIRATION_EXTRACTION_ENABLED")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+});
+
+/// Days before an expiration its owner is notified, unless set for the document
+static DEFAULT_NOTICE_DAYS: Lazy<i32> = Lazy::new(|| {
+    std::env::var("EXPIRATION_NOTICE_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|days| (0..=MAX_NOTICE_DAYS).contains(days))
+        .unwrap_or(30)
+});
+
+/// Finds when documents expire or renew, and notifies their owners ahead of time on
+/// their email, ntfy, Gotify and Slack channels like any other notification
+pub struct ExpirationService {
+    db: Database,
+}
+
+impl ExpirationService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Whether expiration dates should be extracted automatically after OCR
+    pub fn enabled_after_ocr() -> bool {
+        *ENABLED_AFTER_OCR
+    }
+
+    pub fn default_notice_days() -> i32 {
+        *DEFAULT_NOTICE_DAYS
+    }
+
+    /// Record the expiration or renewal date the document's text gives, unless one was
+    /// set by hand. Dates already past are ignored. The recorded expiration, if any.
+    pub async fn extract(&self, document: &Document) -> Result<Option<DocumentExpiration>> {
+        let Some(text) = document.ocr_text.as_deref().or(document.content.as_deref()) else {
+            return Ok(None);
+        };
+        let Some((expires_on, kind)) = expiration_dates::find_expiration(text) else {
+            return Ok(None);
+        };
+        if expires_on < Utc::now().date_naive() {
+            debug!("Document {} expired on {}, not recording it", document.id, expires_on);
+            return Ok(None);
+        }
+
+        if self
+            .db
+            .record_extracted_expiration(document.id, expires_on, kind, Self::default_notice_days())
+            .await?
+        {
+            info!("Document {} {} on {}", document.id, if kind == EXPIRATION_KIND_RENEWAL { "renews" } else { "expires" }, expires_on);
+        }
+        self.db.get_document_expiration(document.id).await
+    }
+
+    /// Send due notifications every hour
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(POLL_SECONDS));
+        loop {
+            interval.tick().await;
+            match self.run_once().await {
+                Ok(sent) if sent > 0 => info!("Sent {} expiration notice(s)", sent),
+                Ok(_) => {}
+                Err(e) => error!("Failed to send expiration notices: {}", e),
+            }
+        }
+    }
+
+    /// Notify the owners of documents whose notice period started; returns how many
+    /// were notified
+    pub async fn run_once(&self) -> Result<usize> {
+        let mut sent = 0;
+        for expiration in self.db.claim_due_expirations(BATCH_SIZE).await? {
+            let locale = i18n::locale_of(&self.db, expiration.user_id).await;
+            match self.db.create_notification(expiration.user_id, &expiration_notification(&expiration, &locale)).await {
+                Ok(_) => sent += 1,
+                Err(e) => {
+                    error!("Failed to send expiration notice of document {}: {}", expiration.document_id, e);
+                    self.db.release_expiration(expiration.document_id).await?;
+                }
+            }
+        }
+        Ok(sent)
+    }
+}
+
+fn expiration_notification(expiration: &DocumentExpiration, locale: &str) -> CreateNotification {
+    let key = if expiration.kind == EXPIRATION_KIND_RENEWAL {
+        "notification.document_renewal"
+    } else {
+        "notification.document_expiration"
+    };
+    let notification = i18n::notification(
+        locale,
+        "warning",
+        key,
+        &[
+            ("filename", expiration.document_filename.clone()),
+            ("date", expiration.expires_on.to_string()),
+            ("days", expiration.days_left.to_string()),
+        ],
+    );
+    CreateNotification {
+        message: match &expiration.note {
+            Some(note) => format!("{} {}", notification.message, note),
+            None => notification.message,
+        },
+        action_url: Some(format!("/documents/{}", expiration.document_id)),
+        metadata: Some(json!({
+            "document_id": expiration.document_id,
+            "expires_on": expiration.expires_on,
+            "kind": expiration.kind,
+        })),
+        ..notification
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_expiration_notification() {
+        let now = Utc::now();
+        let mut expiration = DocumentExpiration {
+            document_id: Uuid::new_v4(),
+            document_filename: "lease.pdf".to_string(),
+            user_id: Uuid::new_v4(),
+            expires_on: NaiveDate::from_ymd_opt(2026, 3, 31).unwrap(),
+            kind: "expiration".to_string(),
+            notice_days: 30,
+            note: None,
+            source: "manual".to_string(),
+            notified_at: Some(now),
+            days_left: 30,
+            set_by: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let notification = expiration_notification(&expiration, "en");
+        assert_eq!(notification.title, "Expiring: lease.pdf");
+        assert_eq!(notification.message, "lease.pdf expires on 2026-03-31, in 30 day(s).");
+        assert_eq!(notification.action_url, Some(format!("/documents/{}", expiration.document_id)));
+
+        expiration.kind = "renewal".to_string();
+        expiration.note = Some("Cancel by registered mail.".to_string());
+        assert_eq!(
+            expiration_notification(&expiration, "en").message,
+            "lease.pdf renews on 2026-03-31, in 30 day(s), unless cancelled before. Cancel by registered mail."
+        );
+    }
+}
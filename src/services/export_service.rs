@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use uuid::Uuid;
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::db::Database;
+use crate::models::{
+    Document, DocumentCustomFields, Export, ExportError, ExportManifest, ExportStatus, ExportedDocument,
+    ExportedGraph, EXPORT_FORMAT_VERSION, EXPORT_MANIFEST_NAME,
+};
+use crate::services::file_service::FileService;
+
+/// Directory under the upload path the archives are written to
+const EXPORTS_DIR: &str = "exports";
+/// Documents written between progress updates
+const PROGRESS_BATCH_SIZE: usize = 25;
+/// Failures kept with an export; later ones are only counted
+const MAX_RECORDED_ERRORS: usize = 100;
+
+/// Writes library exports: a ZIP archive with every document's original file under
+/// `files/` and a `manifest.json` with their metadata, labels, OCR text and knowledge
+/// graphs. Progress is saved after every batch. An archive cannot be continued, so an
+/// export interrupted by a restart is written again from the start.
+pub struct ExportService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl ExportService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Where the finished archive of an export is kept
+    pub fn archive_path(&self, export_id: Uuid) -> PathBuf {
+        self.file_service
+            .get_subdirectory_path(EXPORTS_DIR)
+            .join(format!("{}.zip", export_id))
+    }
+
+    /// Write the exports that were queued or running when the server stopped
+    pub async fn resume_interrupted(&self) -> Result<()> {
+        for export in self.db.get_unfinished_exports().await? {
+            info!("Restarting export {}", export.id);
+            if let Err(e) = self.run(export.id).await {
+                warn!("Export {} stopped: {}", export.id, e);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn run(&self, export_id: Uuid) -> Result<Export> {
+        let export = self
+            .db
+            .get_export(export_id)
+            .await?
+            .ok_or_else(|| anyhow!("Export {} not found", export_id))?;
+        if matches!(export.status, ExportStatus::Completed | ExportStatus::Failed) {
+            return Ok(export);
+        }
+
+        self.db.start_export(export.id).await?;
+        let partial_path = self.archive_path(export.id).with_extension("zip.partial");
+        match self.write_archive(&export, &partial_path).await {
+            Ok(archive_size) => {
+                info!("Export {} completed ({} bytes)", export.id, archive_size);
+                self.db
+                    .finish_export(export.id, ExportStatus::Completed, Some(archive_size), None)
+                    .await
+            }
+            Err(e) => {
+                warn!("Export {} failed: {}", export.id, e);
+                if let Err(e) = tokio::fs::remove_file(&partial_path).await {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        warn!("Failed to remove partial archive {}: {}", partial_path.display(), e);
+                    }
+                }
+                self.db
+                    .finish_export(export.id, ExportStatus::Failed, None, Some(&e.to_string()))
+                    .await
+            }
+        }
+    }
+
+    /// Remove an export's archive, if it was written
+    pub async fn delete_archive(&self, export_id: Uuid) -> Result<()> {
+        match tokio::fs::remove_file(self.archive_path(export_id)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the archive to `partial_path` and move it into place. Returns its size.
+    async fn write_archive(&self, export: &Export, partial_path: &Path) -> Result<i64> {
+        if let Some(dir) = partial_path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let mut writer = ZipWriter::new(File::create(partial_path)?);
+
+        let document_ids = self.db.get_export_document_ids(export.id).await?;
+        let mut documents = Vec::with_capacity(document_ids.len());
+        let mut recorded_errors = 0;
+
+        for batch in document_ids.chunks(PROGRESS_BATCH_SIZE) {
+            let mut batch_documents = self.db.get_documents_by_ids(batch).await?;
+            // Documents deleted since the export was requested are left out
+            batch_documents.sort_by_key(|document| batch.iter().position(|id| *id == document.id));
+            let mut label_ids = self.db.get_document_label_ids(batch).await?;
+            let mut failed = 0;
+            let mut errors = Vec::new();
+
+            for document in batch_documents {
+                let (file, file_hash) = match self.file_service.read_file(&document.file_path).await {
+                    Ok(data) => {
+                        let file_hash = format!("{:x}", Sha256::digest(&data));
+                        let name = format!("files/{}/{}", document.id, archive_file_name(&document.original_filename));
+                        writer = write_entry(writer, name.clone(), data).await?;
+                        (Some(name), Some(file_hash))
+                    }
+                    Err(e) => {
+                        warn!("Export {} could not read the file of document {}: {}", export.id, document.id, e);
+                        failed += 1;
+                        if recorded_errors < MAX_RECORDED_ERRORS {
+                            recorded_errors += 1;
+                            errors.push(ExportError { document_id: document.id, error: e.to_string() });
+                        }
+                        (None, document.file_hash.clone())
+                    }
+                };
+
+                let graph = self.db.get_exported_graph(document.id).await?;
+                let custom_fields = self.db.get_document_custom_fields(document.id).await?;
+                let label_ids = label_ids.remove(&document.id).unwrap_or_default();
+                documents.push(exported_document(document, file, file_hash, label_ids, custom_fields, graph));
+            }
+
+            self.db
+                .update_export_progress(export.id, batch.len() as i32, failed, &errors)
+                .await?;
+        }
+
+        let user_ids: Vec<Uuid> = if export.all_users {
+            documents.iter().map(|document| document.user_id).collect::<BTreeSet<_>>().into_iter().collect()
+        } else {
+            vec![export.user_id]
+        };
+        let manifest = ExportManifest {
+            format_version: EXPORT_FORMAT_VERSION,
+            readur_version: env!("CARGO_PKG_VERSION").to_string(),
+            export_id: export.id,
+            exported_by: export.user_id,
+            exported_at: Utc::now(),
+            users: self.db.get_exported_users(&user_ids).await?,
+            labels: self.db.get_exported_labels(&user_ids).await?,
+            documents,
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest)?;
+        writer = write_entry(writer, EXPORT_MANIFEST_NAME.to_string(), manifest).await?;
+        tokio::task::spawn_blocking(move || writer.finish()).await??;
+
+        let archive_path = self.archive_path(export.id);
+        tokio::fs::rename(partial_path, &archive_path).await?;
+        let archive_size = tokio::fs::metadata(&archive_path).await?.len();
+        Ok(archive_size as i64)
+    }
+}
+
+/// Add a file to the archive off the async runtime, handing the writer back
+async fn write_entry(mut writer: ZipWriter<File>, name: String, data: Vec<u8>) -> Result<ZipWriter<File>> {
+    tokio::task::spawn_blocking(move || -> Result<ZipWriter<File>> {
+        let options = FileOptions::default().large_file(data.len() as u64 > u32::MAX as u64);
+        writer.start_file(name, options)?;
+        writer.write_all(&data)?;
+        Ok(writer)
+    })
+    .await?
+}
+
+/// The last component of a filename, safe to use as an archive entry
+fn archive_file_name(original_filename: &str) -> String {
+    let name: String = original_filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    match name.trim() {
+        "" | "." | ".." => "document".to_string(),
+        name => name.to_string(),
+    }
+}
+
+fn exported_document(
+    document: Document,
+    file: Option<String>,
+    file_hash: Option<String>,
+    label_ids: Vec<Uuid>,
+    custom_fields: Option<DocumentCustomFields>,
+    graph: ExportedGraph,
+) -> ExportedDocument {
+    ExportedDocument {
+        id: document.id,
+        user_id: document.user_id,
+        filename: document.filename,
+        original_filename: document.original_filename,
+        mime_type: document.mime_type,
+        file_size: document.file_size,
+        file_hash,
+        file,
+        tags: document.tags,
+        created_at: document.created_at,
+        updated_at: document.updated_at,
+        original_created_at: document.original_created_at,
+        original_modified_at: document.original_modified_at,
+        source_path: document.source_path,
+        source_type: document.source_type,
+        source_metadata: document.source_metadata,
+        content: document.content,
+        ocr_text: document.ocr_text,
+        ocr_confidence: document.ocr_confidence,
+        ocr_word_count: document.ocr_word_count,
+        ocr_status: document.ocr_status,
+        ocr_completed_at: document.ocr_completed_at,
+        label_ids,
+        custom_fields,
+        graph,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_file_name_drops_directories() {
+        assert_eq!(archive_file_name("invoice.pdf"), "invoice.pdf");
+        assert_eq!(archive_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(archive_file_name("C:\\scans\\receipt.jpg"), "receipt.jpg");
+        assert_eq!(archive_file_name("folder/"), "document");
+        assert_eq!(archive_file_name(".."), "document");
+    }
+}
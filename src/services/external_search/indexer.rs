@@ -0,0 +1,268 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::ExternalSearch;
+use crate::db::Database;
+use crate::errors::panic::spawn_guarded;
+use crate::models::{ContentDestination, Document, EventType, StoredEvent};
+use crate::services::data_residency;
+
+/// Events that change what the engine holds for a document
+const INDEXED_EVENTS: [EventType; 6] = [
+    EventType::DocumentCreated,
+    EventType::DocumentDeleted,
+    EventType::DocumentUpdated,
+    EventType::DocumentLabelsChanged,
+    EventType::OcrCompleted,
+    EventType::AnalysisCompleted,
+];
+
+const EVENT_BATCH: i64 = 500;
+const RESYNC_BATCH: i64 = 500;
+/// Events younger than this are read on a later poll, see `Database::get_events_after`
+const SETTLE_SECONDS: i32 = 5;
+
+/// Keeps the external index in step with the event log
+#[derive(Clone)]
+pub struct ExternalSearchIndexer {
+    db: Database,
+    search: Arc<ExternalSearch>,
+}
+
+impl ExternalSearchIndexer {
+    pub fn new(db: Database, search: Arc<ExternalSearch>) -> Self {
+        Self { db, search }
+    }
+
+    fn engine(&self) -> &'static str {
+        self.search.config.engine.as_str()
+    }
+
+    fn index(&self) -> &str {
+        &self.search.config.index
+    }
+
+    /// Follow the event log until the process exits. An index seen for the first
+    /// time is filled by a full re-sync.
+    pub async fn run(self) {
+        if let Err(e) = self.db.reset_interrupted_external_search_resyncs().await {
+            warn!("Failed to reset interrupted external search re-syncs: {}", e);
+        }
+
+        let mut ready = false;
+        let mut interval = tokio::time::interval(self.search.config.poll_interval);
+        loop {
+            interval.tick().await;
+
+            if !ready {
+                if let Err(e) = self.prepare().await {
+                    warn!("External search index {} is not ready: {}", self.index(), e);
+                    continue;
+                }
+                ready = true;
+            }
+
+            if let Err(e) = self.apply_new_events().await {
+                warn!("Failed to apply events to external search index {}: {}", self.index(), e);
+            }
+        }
+    }
+
+    async fn prepare(&self) -> Result<()> {
+        self.search.client.ensure_index().await?;
+        if self.db.get_external_search_state(self.engine(), self.index()).await?.is_none() {
+            info!("External search index {} has never been filled, starting a full re-sync", self.index());
+            self.start_resync().await?;
+        }
+        Ok(())
+    }
+
+    /// Send documents changed by events after the saved position. Returns the number
+    /// of events read.
+    pub async fn apply_new_events(&self) -> Result<usize> {
+        let mut position = self
+            .db
+            .get_external_search_state(self.engine(), self.index())
+            .await?
+            .and_then(|state| state.last_event_at.zip(state.last_event_id));
+
+        let mut read = 0;
+        loop {
+            let events = self
+                .db
+                .get_events_after(position, &INDEXED_EVENTS, SETTLE_SECONDS, EVENT_BATCH)
+                .await?;
+            let Some(last) = events.last() else {
+                break;
+            };
+            let next: (DateTime<Utc>, Uuid) = (last.occurred_at, last.id);
+
+            let (changed, deleted) = reduce_events(&events);
+            self.index_documents(&changed).await?;
+            self.search.client.delete(&deleted).await?;
+            self.db.save_external_search_position(self.engine(), self.index(), next).await?;
+
+            read += events.len();
+            position = Some(next);
+            if (events.len() as i64) < EVENT_BATCH {
+                break;
+            }
+        }
+        Ok(read)
+    }
+
+    /// Upsert the current state of these documents; any that no longer exist are removed
+    async fn index_documents(&self, document_ids: &[Uuid]) -> Result<()> {
+        if document_ids.is_empty() {
+            return Ok(());
+        }
+        let documents = self.db.get_documents_by_ids(document_ids).await?;
+        let found: HashSet<Uuid> = documents.iter().map(|d| d.id).collect();
+        let missing: Vec<Uuid> = document_ids.iter().copied().filter(|id| !found.contains(id)).collect();
+
+        self.upsert(&documents).await?;
+        self.search.client.delete(&missing).await
+    }
+
+    /// Send documents to the engine, as far as the data residency policy allows
+    async fn upsert(&self, documents: &[Document]) -> Result<()> {
+        let indexed: Vec<Value> = documents.iter().map(|d| self.search.config.mapping.to_document(d)).collect();
+        let document_ids: Vec<Uuid> = documents.iter().map(|d| d.id).collect();
+        let bytes = serde_json::to_vec(&indexed).map_or(0, |body| body.len());
+        data_residency::authorize(
+            self.db.get_pool(),
+            ContentDestination::SearchEngine,
+            &self.search.config.url,
+            &document_ids,
+            bytes,
+        )
+        .await
+        .map_err(|e| anyhow!(e))?;
+        self.search.client.upsert(&indexed).await
+    }
+
+    /// Start a full re-sync in the background. Returns false when one is already running.
+    pub async fn start_resync(&self) -> Result<bool> {
+        if !self.db.start_external_search_resync(self.engine(), self.index()).await? {
+            return Ok(false);
+        }
+        let indexer = self.clone();
+        spawn_guarded(format!("external search re-sync of {}", self.index()), async move {
+            indexer.resync().await;
+        });
+        Ok(true)
+    }
+
+    /// Send every document to the engine. The event position is taken before the first
+    /// page, so changes made while the re-sync runs are applied again afterwards.
+    async fn resync(&self) {
+        let start_position = match self.db.get_latest_event_position(SETTLE_SECONDS).await {
+            Ok(position) => position,
+            Err(e) => {
+                self.finish_resync(0, Err(&e.to_string())).await;
+                return;
+            }
+        };
+
+        let mut sent = 0;
+        match self.send_all_documents(&mut sent).await {
+            Ok(()) => {
+                info!("External search re-sync of {} sent {} documents", self.index(), sent);
+                self.finish_resync(sent, Ok(start_position)).await;
+            }
+            Err(e) => {
+                error!("External search re-sync of {} failed after {} documents: {}", self.index(), sent, e);
+                self.finish_resync(sent, Err(&e.to_string())).await;
+            }
+        }
+    }
+
+    async fn finish_resync(&self, sent: i64, result: std::result::Result<Option<(DateTime<Utc>, Uuid)>, &str>) {
+        if let Err(e) = self
+            .db
+            .finish_external_search_resync(self.engine(), self.index(), sent, result)
+            .await
+        {
+            error!("Failed to record the end of the external search re-sync: {}", e);
+        }
+    }
+
+    async fn send_all_documents(&self, sent: &mut i64) -> Result<()> {
+        let mut after = None;
+        loop {
+            let documents = self.db.get_documents_after_id(after, RESYNC_BATCH).await?;
+            let Some(last) = documents.last() else {
+                return Ok(());
+            };
+            after = Some(last.id);
+
+            self.upsert(&documents).await?;
+            *sent += documents.len() as i64;
+
+            if (documents.len() as i64) < RESYNC_BATCH {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Split a batch of events into documents to re-index and documents to delete; the
+/// last event of a document decides which
+fn reduce_events(events: &[StoredEvent]) -> (Vec<Uuid>, Vec<Uuid>) {
+    let mut latest: HashMap<Uuid, bool> = HashMap::new();
+    for event in events {
+        if let Some(document_id) = event.resource_id {
+            latest.insert(document_id, event.event_type == EventType::DocumentDeleted);
+        }
+    }
+
+    let mut changed = Vec::new();
+    let mut deleted = Vec::new();
+    for (document_id, is_deleted) in latest {
+        if is_deleted {
+            deleted.push(document_id);
+        } else {
+            changed.push(document_id);
+        }
+    }
+    (changed, deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: EventType, document_id: Uuid) -> StoredEvent {
+        StoredEvent {
+            id: Uuid::new_v4(),
+            event_type,
+            schema_version: 1,
+            user_id: None,
+            resource_id: Some(document_id),
+            payload: Value::Null,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_last_event_of_a_document_wins() {
+        let recreated = Uuid::new_v4();
+        let removed = Uuid::new_v4();
+        let events = vec![
+            event(EventType::DocumentDeleted, recreated),
+            event(EventType::DocumentCreated, recreated),
+            event(EventType::OcrCompleted, removed),
+            event(EventType::DocumentDeleted, removed),
+        ];
+
+        let (changed, deleted) = reduce_events(&events);
+
+        assert_eq!(changed, vec![recreated]);
+        assert_eq!(deleted, vec![removed]);
+    }
+}
@@ -0,0 +1,209 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+
+use crate::models::Document;
+
+/// Fields indexed when `EXTERNAL_SEARCH_FIELDS` is not set
+pub const DEFAULT_FIELDS: &str = "original_filename,content,ocr_text,tags,mime_type,created_at";
+
+/// Document columns that can be sent to the search engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentField {
+    Filename,
+    OriginalFilename,
+    Content,
+    OcrText,
+    Tags,
+    MimeType,
+    FileSize,
+    CreatedAt,
+    SourcePath,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// Full-text searchable
+    Text,
+    /// Searchable and filterable list of strings
+    TextList,
+    /// Filterable exact value
+    Keyword,
+    /// Unix seconds, sortable
+    Integer,
+}
+
+impl DocumentField {
+    pub const ALL: [DocumentField; 9] = [
+        DocumentField::Filename,
+        DocumentField::OriginalFilename,
+        DocumentField::Content,
+        DocumentField::OcrText,
+        DocumentField::Tags,
+        DocumentField::MimeType,
+        DocumentField::FileSize,
+        DocumentField::CreatedAt,
+        DocumentField::SourcePath,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentField::Filename => "filename",
+            DocumentField::OriginalFilename => "original_filename",
+            DocumentField::Content => "content",
+            DocumentField::OcrText => "ocr_text",
+            DocumentField::Tags => "tags",
+            DocumentField::MimeType => "mime_type",
+            DocumentField::FileSize => "file_size",
+            DocumentField::CreatedAt => "created_at",
+            DocumentField::SourcePath => "source_path",
+        }
+    }
+
+    pub fn kind(&self) -> FieldKind {
+        match self {
+            DocumentField::Filename
+            | DocumentField::OriginalFilename
+            | DocumentField::Content
+            | DocumentField::OcrText
+            | DocumentField::SourcePath => FieldKind::Text,
+            DocumentField::Tags => FieldKind::TextList,
+            DocumentField::MimeType => FieldKind::Keyword,
+            DocumentField::FileSize | DocumentField::CreatedAt => FieldKind::Integer,
+        }
+    }
+
+    fn value(&self, document: &Document) -> Value {
+        match self {
+            DocumentField::Filename => json!(document.filename),
+            DocumentField::OriginalFilename => json!(document.original_filename),
+            DocumentField::Content => json!(document.content),
+            DocumentField::OcrText => json!(document.ocr_text),
+            DocumentField::Tags => json!(document.tags),
+            DocumentField::MimeType => json!(document.mime_type),
+            DocumentField::FileSize => json!(document.file_size),
+            DocumentField::CreatedAt => json!(document.created_at.timestamp()),
+            DocumentField::SourcePath => json!(document.source_path),
+        }
+    }
+}
+
+/// A document field and the name it has in the engine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedField {
+    pub field: DocumentField,
+    pub name: String,
+}
+
+/// Which document fields are indexed and under what names. `id` and `user_id` are
+/// always indexed; `user_id` keeps each user's searches to their own documents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMapping {
+    pub fields: Vec<MappedField>,
+}
+
+impl FieldMapping {
+    /// Parse `field[:name],...`, e.g. `original_filename:title,ocr_text:text,tags`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut fields: Vec<MappedField> = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (source, name) = entry.split_once(':').unwrap_or((entry, entry));
+            let (source, name) = (source.trim(), name.trim());
+
+            let field = DocumentField::ALL
+                .iter()
+                .copied()
+                .find(|f| f.as_str() == source)
+                .ok_or_else(|| anyhow!("Unknown document field '{}' in search field mapping", source))?;
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(anyhow!("Invalid search field name '{}'", name));
+            }
+            if name == "id" || name == "user_id" {
+                return Err(anyhow!("Search field name '{}' is reserved", name));
+            }
+            if fields.iter().any(|f| f.field == field || f.name == name) {
+                return Err(anyhow!("'{}' is mapped more than once", entry));
+            }
+
+            fields.push(MappedField { field, name: name.to_string() });
+        }
+
+        if !fields.iter().any(|f| matches!(f.field.kind(), FieldKind::Text | FieldKind::TextList)) {
+            return Err(anyhow!("The search field mapping has no searchable field"));
+        }
+        Ok(Self { fields })
+    }
+
+    pub fn name_of(&self, field: DocumentField) -> Option<&str> {
+        self.fields.iter().find(|f| f.field == field).map(|f| f.name.as_str())
+    }
+
+    /// Engine fields queried by full-text search, in mapping order
+    pub fn searchable(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|f| matches!(f.field.kind(), FieldKind::Text | FieldKind::TextList))
+            .map(|f| f.name.as_str())
+            .collect()
+    }
+
+    /// Engine fields searches can filter on, including `user_id`
+    pub fn filterable(&self) -> Vec<&str> {
+        std::iter::once("user_id")
+            .chain(
+                self.fields
+                    .iter()
+                    .filter(|f| matches!(f.field.kind(), FieldKind::TextList | FieldKind::Keyword))
+                    .map(|f| f.name.as_str()),
+            )
+            .collect()
+    }
+
+    /// The document as the engine stores it
+    pub fn to_document(&self, document: &Document) -> Value {
+        let mut object = Map::new();
+        object.insert("id".to_string(), json!(document.id.to_string()));
+        object.insert("user_id".to_string(), json!(document.user_id.to_string()));
+        for mapped in &self.fields {
+            object.insert(mapped.name.clone(), mapped.field.value(document));
+        }
+        Value::Object(object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::document_helpers::create_test_document;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_parse_mapping() {
+        let mapping = FieldMapping::parse("original_filename:title, ocr_text:text,tags,mime_type").unwrap();
+        assert_eq!(mapping.name_of(DocumentField::OriginalFilename), Some("title"));
+        assert_eq!(mapping.name_of(DocumentField::Content), None);
+        assert_eq!(mapping.searchable(), vec!["title", "text", "tags"]);
+        assert_eq!(mapping.filterable(), vec!["user_id", "tags", "mime_type"]);
+
+        assert!(FieldMapping::parse("ocr_text:user_id").is_err());
+        assert!(FieldMapping::parse("ocr_text,ocr_text:text").is_err());
+        assert!(FieldMapping::parse("page_count").is_err());
+        assert!(FieldMapping::parse("mime_type,file_size").is_err());
+        assert!(FieldMapping::parse(DEFAULT_FIELDS).is_ok());
+    }
+
+    #[test]
+    fn test_to_document() {
+        let mapping = FieldMapping::parse("ocr_text:text,tags,created_at").unwrap();
+        let mut document = create_test_document(Uuid::new_v4());
+        document.ocr_text = None;
+        document.tags = vec!["tax".to_string()];
+
+        let indexed = mapping.to_document(&document);
+        assert_eq!(indexed["id"], json!(document.id.to_string()));
+        assert_eq!(indexed["user_id"], json!(document.user_id.to_string()));
+        assert_eq!(indexed["text"], Value::Null);
+        assert_eq!(indexed["tags"], json!(["tax"]));
+        assert_eq!(indexed["created_at"], json!(document.created_at.timestamp()));
+        assert!(indexed.get("content").is_none());
+    }
+}
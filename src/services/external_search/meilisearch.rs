@@ -0,0 +1,140 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder};
+use serde_json::{json, Value};
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::{parse_ids, DocumentField, EngineQuery, ExternalSearchConfig, SearchEngineClient, SearchHits};
+
+/// Meilisearch index of documents. Writes are queued as Meilisearch tasks and
+/// become searchable once the engine has processed them.
+pub struct MeilisearchClient {
+    client: Client,
+    url: String,
+    api_key: Option<String>,
+    index: String,
+    searchable: Vec<String>,
+    filterable: Vec<String>,
+    tags_field: Option<String>,
+    mime_type_field: Option<String>,
+}
+
+impl MeilisearchClient {
+    pub fn new(config: &ExternalSearchConfig) -> Result<Self> {
+        let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+        Ok(Self {
+            client,
+            url: config.url.clone(),
+            api_key: config.api_key.clone(),
+            index: config.index.clone(),
+            searchable: config.mapping.searchable().into_iter().map(String::from).collect(),
+            filterable: config.mapping.filterable().into_iter().map(String::from).collect(),
+            tags_field: config.mapping.name_of(DocumentField::Tags).map(String::from),
+            mime_type_field: config.mapping.name_of(DocumentField::MimeType).map(String::from),
+        })
+    }
+
+    fn index_url(&self, path: &str) -> String {
+        format!("{}/indexes/{}{}", self.url, self.index, path)
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let request = match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        };
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(anyhow!("Meilisearch returned {}: {}", status, body));
+        }
+        Ok(serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+}
+
+#[async_trait]
+impl SearchEngineClient for MeilisearchClient {
+    async fn ensure_index(&self) -> Result<()> {
+        // Creating an index that exists fails only in the queued task, which is harmless
+        self.send(
+            self.client
+                .post(format!("{}/indexes", self.url))
+                .json(&json!({ "uid": self.index, "primaryKey": "id" })),
+        )
+        .await?;
+        self.send(self.client.patch(self.index_url("/settings")).json(&json!({
+            "searchableAttributes": self.searchable,
+            "filterableAttributes": self.filterable,
+        })))
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert(&self, documents: &[Value]) -> Result<()> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        self.send(self.client.post(self.index_url("/documents?primaryKey=id")).json(documents))
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, document_ids: &[Uuid]) -> Result<()> {
+        if document_ids.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<String> = document_ids.iter().map(Uuid::to_string).collect();
+        self.send(self.client.post(self.index_url("/documents/delete-batch")).json(&ids))
+            .await?;
+        Ok(())
+    }
+
+    async fn search(&self, query: &EngineQuery<'_>) -> Result<SearchHits> {
+        let mut filters = Vec::new();
+        if let Some(user_id) = query.user_id {
+            filters.push(format!("user_id = {}", quote(&user_id.to_string())));
+        }
+        for (field, values) in [(&self.tags_field, query.tags), (&self.mime_type_field, query.mime_types)] {
+            if values.is_empty() {
+                continue;
+            }
+            let field = field.as_deref().ok_or_else(|| anyhow!("Filtered field is not indexed"))?;
+            let values: Vec<String> = values.iter().map(|v| quote(v)).collect();
+            filters.push(format!("{} IN [{}]", field, values.join(", ")));
+        }
+
+        let response = self
+            .send(self.client.post(self.index_url("/search")).json(&json!({
+                "q": query.text,
+                "limit": query.limit,
+                "offset": query.offset,
+                "filter": filters,
+                "attributesToRetrieve": ["id"],
+            })))
+            .await?;
+
+        let hits = response["hits"].as_array().cloned().unwrap_or_default();
+        Ok(SearchHits {
+            document_ids: parse_ids(hits.iter().filter_map(|hit| hit["id"].as_str())),
+            total: response["estimatedTotalHits"].as_i64().unwrap_or(hits.len() as i64),
+        })
+    }
+}
+
+/// A string literal in a Meilisearch filter expression
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_filter_values() {
+        assert_eq!(quote("tax"), "\"tax\"");
+        assert_eq!(quote(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+    }
+}
@@ -0,0 +1,176 @@
+//! Optional indexing into an external search engine (Meilisearch or Typesense), fed
+//! from the event log, for corpora too large for Postgres full-text search
+
+pub mod indexer;
+pub mod mapping;
+pub mod meilisearch;
+pub mod typesense;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::error;
+use uuid::Uuid;
+
+pub use mapping::{DocumentField, FieldKind, FieldMapping, MappedField};
+
+use crate::models::ContentDestination;
+use crate::services::data_residency;
+
+use meilisearch::MeilisearchClient;
+use typesense::TypesenseClient;
+
+const DEFAULT_INDEX: &str = "readur_documents";
+const DEFAULT_POLL_SECONDS: u64 = 5;
+
+static EXTERNAL_SEARCH: Lazy<Option<Arc<ExternalSearch>>> = Lazy::new(|| {
+    let config = ExternalSearchConfig::from_env().and_then(|config| match config {
+        Some(config) => {
+            data_residency::policy()
+                .check(ContentDestination::SearchEngine, &config.url)
+                .map_err(|reason| anyhow!("the data residency policy keeps document text from it: {}", reason))?;
+            Ok(Some(config))
+        }
+        None => Ok(None),
+    });
+    match config.and_then(|config| config.map(ExternalSearch::new).transpose()) {
+        Ok(search) => search.map(Arc::new),
+        Err(e) => {
+            error!("External search engine disabled: {}", e);
+            None
+        }
+    }
+});
+
+/// The external search engine configured through the environment, if any
+pub fn configured() -> Option<Arc<ExternalSearch>> {
+    EXTERNAL_SEARCH.clone()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineKind {
+    Meilisearch,
+    Typesense,
+}
+
+impl EngineKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EngineKind::Meilisearch => "meilisearch",
+            EngineKind::Typesense => "typesense",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExternalSearchConfig {
+    pub engine: EngineKind,
+    pub url: String,
+    pub api_key: Option<String>,
+    /// Meilisearch index or Typesense collection
+    pub index: String,
+    pub mapping: FieldMapping,
+    /// Answer searches from the engine instead of Postgres
+    pub query_enabled: bool,
+    /// How often the indexer reads new events
+    pub poll_interval: Duration,
+}
+
+impl ExternalSearchConfig {
+    /// None unless `EXTERNAL_SEARCH_ENGINE` is set
+    pub fn from_env() -> Result<Option<Self>> {
+        let engine = match std::env::var("EXTERNAL_SEARCH_ENGINE").ok().filter(|v| !v.trim().is_empty()) {
+            None => return Ok(None),
+            Some(engine) => match engine.trim().to_lowercase().as_str() {
+                "meilisearch" => EngineKind::Meilisearch,
+                "typesense" => EngineKind::Typesense,
+                other => return Err(anyhow!("Unknown EXTERNAL_SEARCH_ENGINE '{}' (expected meilisearch or typesense)", other)),
+            },
+        };
+        let url = std::env::var("EXTERNAL_SEARCH_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .ok_or_else(|| anyhow!("EXTERNAL_SEARCH_URL is required when EXTERNAL_SEARCH_ENGINE is set"))?;
+        let fields = std::env::var("EXTERNAL_SEARCH_FIELDS").unwrap_or_else(|_| mapping::DEFAULT_FIELDS.to_string());
+
+        Ok(Some(Self {
+            engine,
+            url: url.trim_end_matches('/').to_string(),
+            api_key: std::env::var("EXTERNAL_SEARCH_API_KEY").ok().filter(|v| !v.is_empty()),
+            index: std::env::var("EXTERNAL_SEARCH_INDEX").unwrap_or_else(|_| DEFAULT_INDEX.to_string()),
+            mapping: FieldMapping::parse(&fields)?,
+            query_enabled: std::env::var("EXTERNAL_SEARCH_QUERY")
+                .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+                .unwrap_or(false),
+            poll_interval: Duration::from_secs(
+                std::env::var("EXTERNAL_SEARCH_POLL_SECONDS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_POLL_SECONDS)
+                    .max(1),
+            ),
+        }))
+    }
+}
+
+/// A full-text query sent to the engine. Filters use the document fields' mapped names.
+#[derive(Debug, Clone)]
+pub struct EngineQuery<'a> {
+    pub text: &'a str,
+    /// Restrict to one user's documents; None searches everyone's (admins)
+    pub user_id: Option<Uuid>,
+    pub tags: &'a [String],
+    pub mime_types: &'a [String],
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchHits {
+    /// Matching documents, best first
+    pub document_ids: Vec<Uuid>,
+    /// Total matches, which engines may estimate
+    pub total: i64,
+}
+
+#[async_trait]
+pub trait SearchEngineClient: Send + Sync {
+    /// Create the index if needed and apply the mapping's searchable and filterable fields
+    async fn ensure_index(&self) -> Result<()>;
+
+    /// Add or replace documents produced by `FieldMapping::to_document`
+    async fn upsert(&self, documents: &[serde_json::Value]) -> Result<()>;
+
+    async fn delete(&self, document_ids: &[Uuid]) -> Result<()>;
+
+    async fn search(&self, query: &EngineQuery<'_>) -> Result<SearchHits>;
+}
+
+pub struct ExternalSearch {
+    pub config: ExternalSearchConfig,
+    pub client: Arc<dyn SearchEngineClient>,
+}
+
+impl ExternalSearch {
+    pub fn new(config: ExternalSearchConfig) -> Result<Self> {
+        let client: Arc<dyn SearchEngineClient> = match config.engine {
+            EngineKind::Meilisearch => Arc::new(MeilisearchClient::new(&config)?),
+            EngineKind::Typesense => Arc::new(TypesenseClient::new(&config)?),
+        };
+        Ok(Self { config, client })
+    }
+
+    /// Whether a search with these filters can be answered by the engine; filtering on
+    /// a field that is not indexed has to fall back to Postgres
+    pub fn can_filter(&self, tags: bool, mime_types: bool) -> bool {
+        (!tags || self.config.mapping.name_of(DocumentField::Tags).is_some())
+            && (!mime_types || self.config.mapping.name_of(DocumentField::MimeType).is_some())
+    }
+}
+
+/// Parse the IDs of engine hits, skipping any that are not document IDs
+pub(crate) fn parse_ids<'a>(ids: impl Iterator<Item = &'a str>) -> Vec<Uuid> {
+    ids.filter_map(|id| Uuid::parse_str(id).ok()).collect()
+}
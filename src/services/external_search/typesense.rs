@@ -0,0 +1,196 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::{parse_ids, DocumentField, EngineQuery, ExternalSearchConfig, FieldKind, FieldMapping, SearchEngineClient, SearchHits};
+
+const API_KEY_HEADER: &str = "X-TYPESENSE-API-KEY";
+
+/// Typesense collection of documents
+pub struct TypesenseClient {
+    client: Client,
+    url: String,
+    api_key: Option<String>,
+    collection: String,
+    mapping: FieldMapping,
+}
+
+impl TypesenseClient {
+    pub fn new(config: &ExternalSearchConfig) -> Result<Self> {
+        let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+        Ok(Self {
+            client,
+            url: config.url.clone(),
+            api_key: config.api_key.clone(),
+            collection: config.index.clone(),
+            mapping: config.mapping.clone(),
+        })
+    }
+
+    fn collection_url(&self, path: &str) -> String {
+        format!("{}/collections/{}{}", self.url, self.collection, path)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.header(API_KEY_HEADER, key),
+            None => request,
+        }
+    }
+
+    async fn send_text(&self, request: RequestBuilder) -> Result<String> {
+        let response = self.authorize(request).send().await?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(anyhow!("Typesense returned {}: {}", status, body));
+        }
+        Ok(body)
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let body = self.send_text(request).await?;
+        Ok(serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+}
+
+/// Collection schema for the mapping; every mapped field is optional so documents
+/// without OCR text or a source path can still be indexed
+pub fn collection_schema(collection: &str, mapping: &FieldMapping) -> Value {
+    let mut fields = vec![json!({ "name": "user_id", "type": "string", "facet": true })];
+    for mapped in &mapping.fields {
+        let (field_type, facet) = match mapped.field.kind() {
+            FieldKind::Text => ("string", false),
+            FieldKind::TextList => ("string[]", true),
+            FieldKind::Keyword => ("string", true),
+            FieldKind::Integer => ("int64", false),
+        };
+        fields.push(json!({ "name": mapped.name, "type": field_type, "facet": facet, "optional": true }));
+    }
+    json!({ "name": collection, "fields": fields })
+}
+
+#[async_trait]
+impl SearchEngineClient for TypesenseClient {
+    async fn ensure_index(&self) -> Result<()> {
+        let response = self.authorize(self.client.get(self.collection_url(""))).send().await?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => {
+                self.send(
+                    self.client
+                        .post(format!("{}/collections", self.url))
+                        .json(&collection_schema(&self.collection, &self.mapping)),
+                )
+                .await?;
+                Ok(())
+            }
+            status => Err(anyhow!("Typesense returned {} for collection {}", status, self.collection)),
+        }
+    }
+
+    async fn upsert(&self, documents: &[Value]) -> Result<()> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let body = documents.iter().map(Value::to_string).collect::<Vec<_>>().join("\n");
+        let results = self
+            .send_text(
+                self.client
+                    .post(self.collection_url("/documents/import?action=upsert"))
+                    .header(reqwest::header::CONTENT_TYPE, "text/plain")
+                    .body(body),
+            )
+            .await?;
+
+        // The import answers one JSON line per document
+        let failures: Vec<String> = results
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .filter(|result| result["success"].as_bool() != Some(true))
+            .map(|result| result["error"].as_str().unwrap_or("unknown error").to_string())
+            .collect();
+        if let Some(first) = failures.first() {
+            return Err(anyhow!("Typesense rejected {} of {} documents: {}", failures.len(), documents.len(), first));
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, document_ids: &[Uuid]) -> Result<()> {
+        if document_ids.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<String> = document_ids.iter().map(|id| format!("`{}`", id)).collect();
+        self.send(
+            self.client
+                .delete(self.collection_url("/documents"))
+                .query(&[("filter_by", format!("id:[{}]", ids.join(",")))]),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn search(&self, query: &EngineQuery<'_>) -> Result<SearchHits> {
+        let mut filters = Vec::new();
+        if let Some(user_id) = query.user_id {
+            filters.push(format!("user_id:={}", literal(&user_id.to_string())));
+        }
+        for (field, values) in [(DocumentField::Tags, query.tags), (DocumentField::MimeType, query.mime_types)] {
+            if values.is_empty() {
+                continue;
+            }
+            let name = self.mapping.name_of(field).ok_or_else(|| anyhow!("Filtered field is not indexed"))?;
+            let values: Vec<String> = values.iter().map(|v| literal(v)).collect();
+            filters.push(format!("{}:=[{}]", name, values.join(",")));
+        }
+
+        let mut params = vec![
+            ("q", query.text.to_string()),
+            ("query_by", self.mapping.searchable().join(",")),
+            ("include_fields", "id".to_string()),
+            ("limit", query.limit.to_string()),
+            ("offset", query.offset.to_string()),
+        ];
+        if !filters.is_empty() {
+            params.push(("filter_by", filters.join(" && ")));
+        }
+
+        let response = self
+            .send(self.client.get(self.collection_url("/documents/search")).query(&params))
+            .await?;
+
+        let hits = response["hits"].as_array().cloned().unwrap_or_default();
+        Ok(SearchHits {
+            document_ids: parse_ids(hits.iter().filter_map(|hit| hit["document"]["id"].as_str())),
+            total: response["found"].as_i64().unwrap_or(hits.len() as i64),
+        })
+    }
+}
+
+/// A filter value; backticks delimit it and cannot be escaped, so they are dropped
+fn literal(value: &str) -> String {
+    format!("`{}`", value.replace('`', ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collection_schema() {
+        let mapping = FieldMapping::parse("ocr_text:text,tags,mime_type,created_at").unwrap();
+        let schema = collection_schema("docs", &mapping);
+
+        assert_eq!(schema["name"], "docs");
+        let fields = schema["fields"].as_array().unwrap();
+        assert_eq!(fields[0], json!({ "name": "user_id", "type": "string", "facet": true }));
+        assert_eq!(fields[1]["type"], "string");
+        assert_eq!(fields[2]["type"], "string[]");
+        assert_eq!(fields[3]["facet"], true);
+        assert_eq!(fields[4]["type"], "int64");
+        assert_eq!(literal("a`b"), "`ab`");
+    }
+}
@@ -0,0 +1,219 @@
+//! Requeueing of failed documents once the problem behind them is fixed.
+//!
+//! OCR failures of an existing document queue it for OCR again. Files refused at
+//! ingestion are kept in the failed directory so they can go through ingestion again;
+//! a failure that happens again is recorded as a new failed document, which shows up
+//! in the file's failure history.
+
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::ingestion::document_ingestion::{
+    DeduplicationPolicy, DocumentIngestionRequest, DocumentIngestionService, IngestionResult,
+};
+use crate::models::{FailedDocument, FailedDocumentRequeue, RequeueAction, UserRole};
+use crate::ocr::queue::OcrQueueService;
+use crate::services::file_service::FileService;
+
+/// Directory of the upload path files refused at ingestion are kept in
+pub const FAILED_DIR: &str = "failed";
+
+/// Priority of requeued OCR, that of a normal upload
+const REQUEUE_OCR_PRIORITY: i32 = 5;
+
+/// Keep the file of a failed ingestion so it can be requeued. None when it could not
+/// be written, which only means the failure cannot be requeued.
+pub async fn keep_failed_file(file_service: &FileService, failed_document_id: Uuid, data: &[u8]) -> Option<String> {
+    let directory = file_service.get_subdirectory_path(FAILED_DIR);
+    // Named by id only, like quarantined files, so the file cannot be opened by its extension
+    let file_path = directory.join(failed_document_id.to_string());
+
+    let written = async {
+        tokio::fs::create_dir_all(&directory).await?;
+        tokio::fs::write(&file_path, data).await
+    };
+    match written.await {
+        Ok(()) => Some(file_path.to_string_lossy().to_string()),
+        Err(e) => {
+            warn!("Failed to keep the file of failed document {}: {}", failed_document_id, e);
+            None
+        }
+    }
+}
+
+/// Whether a failure with this reason can succeed when tried again. Duplicates and
+/// infected files never do.
+pub fn is_retryable(failure_reason: &str) -> bool {
+    !matches!(failure_reason, "duplicate_content" | "duplicate_filename" | "virus_detected")
+}
+
+/// Why a failed document was not requeued
+#[derive(Debug)]
+pub enum RequeueError {
+    /// Already requeued, or nothing to retry with
+    Conflict(String),
+    /// The file went through ingestion again and failed again
+    FailedAgain(String),
+    Failed(anyhow::Error),
+}
+
+impl From<anyhow::Error> for RequeueError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Failed(e)
+    }
+}
+
+pub struct FailedDocumentService {
+    db: Database,
+    file_service: FileService,
+    queue_service: Arc<OcrQueueService>,
+}
+
+impl FailedDocumentService {
+    pub fn new(db: Database, file_service: FileService, queue_service: Arc<OcrQueueService>) -> Self {
+        Self { db, file_service, queue_service }
+    }
+
+    /// Try a failed document again: OCR failures of a document that still exists are
+    /// queued for OCR, otherwise the kept file goes through ingestion again
+    pub async fn requeue(
+        &self,
+        failed: &FailedDocument,
+        user_id: Uuid,
+        user_role: UserRole,
+    ) -> Result<FailedDocumentRequeue, RequeueError> {
+        if !is_retryable(&failed.failure_reason) {
+            return Err(RequeueError::Conflict(format!(
+                "Failures for {} cannot be retried",
+                failed.failure_reason
+            )));
+        }
+        if self.db.is_failed_document_requeued(failed.id).await? {
+            return Err(RequeueError::Conflict("The failure was already requeued".to_string()));
+        }
+
+        let ocr_document = match failed.existing_document_id.filter(|_| failed.failure_stage == "ocr") {
+            Some(document_id) => self.db.get_document_by_id(document_id, user_id, user_role).await?,
+            None => None,
+        };
+        if ocr_document.as_ref().is_some_and(|document| document.ocr_status.as_deref() == Some("processing")) {
+            return Err(RequeueError::Conflict("OCR is already in progress for the document".to_string()));
+        }
+
+        self.db.record_failed_document_retry(failed.id).await?;
+        let requeue = match ocr_document {
+            Some(document) => {
+                self.queue_service
+                    .enqueue_document(document.id, REQUEUE_OCR_PRIORITY, document.file_size)
+                    .await?;
+                FailedDocumentRequeue {
+                    failed_document_id: failed.id,
+                    action: RequeueAction::Ocr,
+                    document_id: Some(document.id),
+                    message: "Document queued for OCR processing".to_string(),
+                }
+            }
+            None => self.reingest(failed).await?,
+        };
+
+        if !self.db.mark_failed_document_requeued(failed.id, requeue.document_id).await? {
+            return Err(RequeueError::Conflict("The failure was already requeued".to_string()));
+        }
+        info!(
+            "Requeued failed document {} ({:?}, document {:?})",
+            failed.id, requeue.action, requeue.document_id
+        );
+        Ok(requeue)
+    }
+
+    async fn reingest(&self, failed: &FailedDocument) -> Result<FailedDocumentRequeue, RequeueError> {
+        let Some(file_path) = failed.file_path.as_deref() else {
+            return Err(RequeueError::Conflict(
+                "The file was not kept; ingest it again from its source".to_string(),
+            ));
+        };
+        // Kept files are plain local files; others are where the document was stored
+        let kept_file = self.file_service.get_subdirectory_path(FAILED_DIR).join(failed.id.to_string());
+        let is_kept_file = kept_file.to_string_lossy() == file_path;
+        let file_data = if is_kept_file {
+            tokio::fs::read(&kept_file).await.map_err(anyhow::Error::from)
+        } else {
+            self.file_service.read_file(file_path).await
+        }
+        .map_err(|e| RequeueError::Conflict(format!("The file cannot be read: {}", e)))?;
+
+        let request = DocumentIngestionRequest {
+            filename: failed.filename.clone(),
+            original_filename: failed.original_filename.clone().unwrap_or_else(|| failed.filename.clone()),
+            file_data,
+            mime_type: failed
+                .mime_type
+                .clone()
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+            user_id: failed.user_id,
+            deduplication_policy: DeduplicationPolicy::ReturnExisting,
+            source_type: Some(failed.ingestion_source.clone()),
+            source_id: None,
+            original_created_at: None,
+            original_modified_at: None,
+            source_path: failed.original_path.clone(),
+            file_permissions: None,
+            file_owner: None,
+            file_group: None,
+            source_metadata: None,
+        };
+
+        let ingestion = DocumentIngestionService::new(self.db.clone(), self.file_service.clone());
+        let (document_id, message) = match ingestion.ingest_document(request).await {
+            Ok(IngestionResult::Created(document)) | Ok(IngestionResult::NewVersion(document)) => {
+                if let Err(e) = self
+                    .queue_service
+                    .enqueue_document(document.id, REQUEUE_OCR_PRIORITY, document.file_size)
+                    .await
+                {
+                    warn!("Failed to queue requeued document {} for OCR: {}", document.id, e);
+                }
+                (document.id, "File ingested again".to_string())
+            }
+            Ok(IngestionResult::ExistingDocument(document)) => {
+                (document.id, "The content is already in the library".to_string())
+            }
+            Ok(IngestionResult::Skipped { existing_document_id, .. })
+            | Ok(IngestionResult::TrackedAsDuplicate { existing_document_id }) => {
+                (existing_document_id, "The content is already in the library".to_string())
+            }
+            // Recorded again as a failed document by the ingestion itself
+            Err(e) => return Err(RequeueError::FailedAgain(e.to_string())),
+        };
+
+        if is_kept_file {
+            if let Err(e) = tokio::fs::remove_file(&kept_file).await {
+                warn!("Failed to remove the kept file {}: {}", file_path, e);
+            }
+        }
+
+        Ok(FailedDocumentRequeue {
+            failed_document_id: failed.id,
+            action: RequeueAction::Ingestion,
+            document_id: Some(document_id),
+            message,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates_and_infected_files_are_not_retryable() {
+        assert!(!is_retryable("duplicate_content"));
+        assert!(!is_retryable("duplicate_filename"));
+        assert!(!is_retryable("virus_detected"));
+        assert!(is_retryable("policy_violation"));
+        assert!(is_retryable("ocr_timeout"));
+        assert!(is_retryable("storage_error"));
+    }
+}
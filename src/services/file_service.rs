@@ -1,4 +1,5 @@
 use anyhow::Result;
+use sha2::{Digest, Sha256};
 use chrono::Utc;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -6,9 +7,14 @@ use tokio::fs;
 use uuid::Uuid;
 use tracing::{info, warn, error};
 
+use crate::db::{workspaces::WorkspaceScoped, Database};
 use crate::models::Document;
+use crate::services::encryption::{self, EncryptionService};
+use crate::services::office_preview;
+use crate::services::storage_migration_service;
 use crate::services::s3_service::S3Service;
-use crate::storage::{StorageBackend, StorageConfig, factory};
+use crate::storage::{self, FileStream, StorageBackend, StorageConfig, factory};
+use crate::storage::azure::AZURE_PATH_PREFIX;
 
 #[cfg(feature = "ocr")]
 use image::{DynamicImage, ImageFormat, imageops::FilterType};
@@ -21,6 +27,18 @@ pub struct FileService {
     /// Legacy S3 service reference for backward compatibility
     /// TODO: Remove this after all usage sites are migrated
     s3_service: Option<Arc<S3Service>>,
+    /// Encrypts stored files with the owner's key when configured
+    encryption: Option<Arc<EncryptionService>>,
+    /// Other backends that files stored before a change of backend are read from
+    read_backends: Vec<Arc<dyn StorageBackend>>,
+    /// Reference-counted content blobs shared by documents with identical files
+    blobs: Option<BlobStore>,
+}
+
+#[derive(Clone)]
+struct BlobStore {
+    db: Database,
+    deduplicate: bool,
 }
 
 impl FileService {
@@ -33,6 +51,9 @@ impl FileService {
             upload_path,
             storage: Arc::new(local_backend),
             s3_service: None,
+            encryption: None,
+            read_backends: Vec::new(),
+            blobs: None,
         }
     }
 
@@ -44,6 +65,9 @@ impl FileService {
             upload_path,
             storage: storage_backend,
             s3_service: Some(s3_service),
+            encryption: None,
+            read_backends: Vec::new(),
+            blobs: None,
         }
     }
     
@@ -53,7 +77,54 @@ impl FileService {
             upload_path,
             storage,
             s3_service: None, // New API doesn't need legacy S3 reference
+            encryption: None,
+            read_backends: Vec::new(),
+            blobs: None,
+        }
+    }
+
+    /// Encrypt files stored from now on and decrypt encrypted files on read
+    pub fn with_encryption(mut self, encryption: Arc<EncryptionService>) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    pub fn encryption(&self) -> Option<&Arc<EncryptionService>> {
+        self.encryption.as_ref()
+    }
+
+    /// Read files whose path belongs to another backend (`s3://`, `azure://` or a
+    /// local path) from that backend instead of the default one
+    pub fn with_read_backends(mut self, read_backends: Vec<Arc<dyn StorageBackend>>) -> Self {
+        self.read_backends = read_backends;
+        self
+    }
+
+    /// Track content blobs in `db`, so their files are removed with their last
+    /// reference. With `deduplicate`, document files are stored as blobs shared by
+    /// every document with the same content; encrypted files are never shared.
+    pub fn with_blob_store(mut self, db: Database, deduplicate: bool) -> Self {
+        self.blobs = Some(BlobStore { db, deduplicate });
+        self
+    }
+
+    /// The backend a stored path belongs to, falling back to the default backend
+    fn backend_for(&self, file_path: &str) -> &Arc<dyn StorageBackend> {
+        let storage_type = if file_path.starts_with("s3://") {
+            "s3"
+        } else if file_path.starts_with(AZURE_PATH_PREFIX) {
+            "azure"
+        } else {
+            "local"
+        };
+
+        if self.storage.storage_type() == storage_type {
+            return &self.storage;
         }
+        self.read_backends
+            .iter()
+            .find(|backend| backend.storage_type() == storage_type)
+            .unwrap_or(&self.storage)
     }
     
     /// Create FileService from storage configuration (factory pattern)
@@ -80,6 +151,20 @@ impl FileService {
         Ok(())
     }
     
+    /// Write a small file and remove it again, to check that new documents can be stored
+    pub async fn check_writable(&self) -> Result<()> {
+        let probe = format!(".health-{}", Uuid::new_v4());
+        if self.storage.storage_type() == "local" {
+            let path = Path::new(&self.upload_path).join(probe);
+            fs::write(&path, b"ok").await?;
+            fs::remove_file(&path).await?;
+            return Ok(());
+        }
+
+        let path = self.storage.store_content_blob(&probe, b"ok").await?;
+        self.storage.delete_content_blob(&path).await
+    }
+
     /// Initialize the upload directory structure (legacy method for local storage)
     pub async fn initialize_directory_structure(&self) -> Result<()> {
         // For non-local storage, this is a no-op
@@ -93,6 +178,7 @@ impl FileService {
         let directories = [
             "documents",        // Final uploaded documents
             "thumbnails",       // Document thumbnails
+            "previews",         // PDF renditions of office documents
             "processed_images", // OCR processed images for review
             "temp",            // Temporary files during processing
             "backups",         // Document backups
@@ -125,6 +211,11 @@ impl FileService {
         self.get_subdirectory_path("thumbnails")
     }
 
+    /// Get the directory holding cached PDF renditions of office documents
+    pub fn get_previews_path(&self) -> PathBuf {
+        self.get_subdirectory_path("previews")
+    }
+
     /// Get the processed images directory path
     pub fn get_processed_images_path(&self) -> PathBuf {
         self.get_subdirectory_path("processed_images")
@@ -215,25 +306,198 @@ impl FileService {
 
     /// Save file for a specific document (works with both local and S3)
     pub async fn save_document_file(&self, user_id: Uuid, document_id: Uuid, filename: &str, data: &[u8]) -> Result<String> {
-        let storage_path = self.storage.store_document(user_id, document_id, filename, data).await?;
+        let data = self.encrypt_for_storage(user_id, Some(document_id), data).await?;
+        let storage_path = self.storage.store_document(user_id, document_id, filename, &data).await?;
         info!("Saved document via storage backend: {}", storage_path);
         Ok(storage_path)
     }
 
+    /// Save the file of a new document or version whose content has SHA-256
+    /// `file_hash`. With deduplication on, an existing blob of the same content
+    /// is reused instead of storing the file again.
+    pub async fn store_document_content(&self, user_id: Uuid, document_id: Uuid, filename: &str, data: &[u8], file_hash: &str) -> Result<String> {
+        let blobs = match &self.blobs {
+            Some(blobs) if blobs.deduplicate && self.encryption.is_none() => blobs,
+            _ => return self.save_document_file(user_id, document_id, filename, data).await,
+        };
+
+        if let Some(blob) = blobs.db.acquire_file_blob(file_hash).await? {
+            info!("Document {} shares stored content {}", document_id, blob.storage_path);
+            return Ok(blob.storage_path);
+        }
+
+        let storage_path = self.storage.store_content_blob(&storage::blob_key(file_hash, filename), data).await?;
+        let blob = match blobs.db.insert_file_blob(file_hash, &storage_path, data.len() as i64).await {
+            Ok(blob) => blob,
+            Err(e) => {
+                if let Err(delete_error) = self.storage.delete_content_blob(&storage_path).await {
+                    warn!("Failed to remove unrecorded blob {}: {}", storage_path, delete_error);
+                }
+                return Err(e);
+            }
+        };
+        if blob.storage_path != storage_path {
+            // The same content was stored concurrently; keep that copy
+            if let Err(e) = self.storage.delete_content_blob(&storage_path).await {
+                warn!("Failed to remove duplicate blob {}: {}", storage_path, e);
+            }
+        }
+        info!("Saved document content as blob: {}", blob.storage_path);
+        Ok(blob.storage_path)
+    }
+
+    /// Drop a document's reference to the blob at `file_path`, removing the file
+    /// when it was the last one. False when the path is not a blob.
+    pub async fn release_blob(&self, file_path: &str) -> Result<bool> {
+        let Some(blobs) = &self.blobs else {
+            return Ok(false);
+        };
+        if !storage::is_blob_path(file_path) {
+            return Ok(false);
+        }
+
+        match blobs.db.release_file_blob(file_path).await? {
+            Some(0) => {
+                if blobs.db.delete_unreferenced_file_blob(file_path).await? {
+                    self.invalidate_thumbnail(file_path).await;
+                    self.backend_for(file_path).delete_content_blob(file_path).await?;
+                    info!("Deleted blob {} with its last reference", file_path);
+                }
+            }
+            Some(_) => {}
+            None => warn!("Blob {} has no recorded references", file_path),
+        }
+        Ok(true)
+    }
+
+    /// Whether document files are stored as shared content blobs, which stay in place
+    /// whatever the storage layout
+    pub fn deduplicates(&self) -> bool {
+        self.blobs.as_ref().is_some_and(|blobs| blobs.deduplicate) && self.encryption.is_none()
+    }
+
+    /// Whether a stored path is in the library organized by the storage layout template
+    pub fn is_library_path(&self, file_path: &str) -> bool {
+        match file_path.strip_prefix("s3://").or_else(|| file_path.strip_prefix(AZURE_PATH_PREFIX)) {
+            Some(key) => key.starts_with(&format!("{}/", storage::LIBRARY_DIR)),
+            None => Path::new(file_path).starts_with(Path::new(&self.upload_path).join(storage::LIBRARY_DIR)),
+        }
+    }
+
+    /// Save a document file at `relative_path` of the storage layout
+    pub async fn save_library_file(&self, user_id: Uuid, relative_path: &str, data: &[u8]) -> Result<String> {
+        let data = self.encrypt_for_storage(user_id, None, data).await?;
+        let storage_path = self.storage.store_library_file(relative_path, &data).await?;
+        info!("Saved document in library layout: {}", storage_path);
+        Ok(storage_path)
+    }
+
+    /// Copy a stored document file byte for byte to `relative_path` of the storage layout,
+    /// or back to the backend's own layout when None, and read it back to verify the copy.
+    /// Returns the new path; the source file is left in place.
+    pub async fn copy_document_file(
+        &self,
+        user_id: Uuid,
+        document_id: Uuid,
+        filename: &str,
+        file_path: &str,
+        relative_path: Option<&str>,
+    ) -> Result<String> {
+        let data = self.read_file_raw(file_path).await?;
+        let target_path = match relative_path {
+            Some(relative_path) => self.storage.store_library_file(relative_path, &data).await?,
+            None => self.storage.store_document(user_id, document_id, filename, &data).await?,
+        };
+
+        let stored = self.storage.retrieve_file(&target_path).await?;
+        if Sha256::digest(&stored) != Sha256::digest(&data) {
+            return Err(anyhow::anyhow!("Copy at {} failed hash verification", target_path));
+        }
+        Ok(target_path)
+    }
+
+    /// Delete just the file at `file_path`, keeping the document's thumbnail
+    pub async fn delete_stored_file(&self, file_path: &str) -> Result<()> {
+        self.invalidate_thumbnail(file_path).await;
+        self.backend_for(file_path).delete_stored_file(file_path).await
+    }
+
     /// Save thumbnail (works with both local and S3)
     pub async fn save_thumbnail(&self, user_id: Uuid, document_id: Uuid, data: &[u8]) -> Result<String> {
-        let storage_path = self.storage.store_thumbnail(user_id, document_id, data).await?;
+        let data = self.encrypt_for_storage(user_id, Some(document_id), data).await?;
+        let storage_path = self.storage.store_thumbnail(user_id, document_id, &data).await?;
         info!("Saved thumbnail via storage backend: {}", storage_path);
         Ok(storage_path)
     }
 
     /// Save processed image (works with both local and S3)
     pub async fn save_processed_image(&self, user_id: Uuid, document_id: Uuid, data: &[u8]) -> Result<String> {
-        let storage_path = self.storage.store_processed_image(user_id, document_id, data).await?;
+        let data = self.encrypt_for_storage(user_id, Some(document_id), data).await?;
+        let storage_path = self.storage.store_processed_image(user_id, document_id, &data).await?;
         info!("Saved processed image via storage backend: {}", storage_path);
         Ok(storage_path)
     }
 
+    /// Seal a file of `user_id` with the key of the document's workspace, or the user's
+    /// own key for personal documents
+    async fn encrypt_for_storage<'a>(&self, user_id: Uuid, document_id: Option<Uuid>, data: &'a [u8]) -> Result<std::borrow::Cow<'a, [u8]>> {
+        match &self.encryption {
+            Some(encryption) => {
+                let owner = encryption
+                    .key_owner(user_id, document_id.map(|id| (WorkspaceScoped::Document, id)))
+                    .await?;
+                Ok(std::borrow::Cow::Owned(encryption.encrypt_for(owner, data).await?))
+            }
+            None => Ok(std::borrow::Cow::Borrowed(data)),
+        }
+    }
+
+    /// Re-encrypt a document's file with the active version of its workspace's or owner's
+    /// key, encrypting it for the first time if it was stored before encryption was
+    /// enabled. Returns the new storage path, or None when the file already uses the
+    /// active key.
+    pub async fn reencrypt_document_file(&self, user_id: Uuid, document_id: Uuid, filename: &str, file_path: &str) -> Result<Option<String>> {
+        let encryption = self.encryption
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Encryption is not configured"))?;
+
+        let stored = self.read_file_raw(file_path).await?;
+        let owner = encryption.key_owner(user_id, Some((WorkspaceScoped::Document, document_id))).await?;
+        let active_key = encryption.active_key(owner).await?;
+        if encryption::sealed_key_id(&stored) == Some(active_key.id) {
+            return Ok(None);
+        }
+
+        let plaintext = encryption.decrypt(stored).await?;
+        let new_path = self.save_document_file(user_id, document_id, filename, &plaintext).await?;
+        Ok(Some(new_path))
+    }
+
+    /// A local path external tools such as Tesseract can read the file from. Encrypted
+    /// files are decrypted into a temporary copy that is removed when the guard drops.
+    pub async fn plaintext_path(&self, file_path: &str) -> Result<PlaintextPath> {
+        let Some(encryption) = &self.encryption else {
+            return Ok(PlaintextPath { path: file_path.to_string(), temporary: false });
+        };
+
+        let stored = self.read_file_raw(file_path).await?;
+        if !encryption::is_encrypted(&stored) {
+            return Ok(PlaintextPath { path: file_path.to_string(), temporary: false });
+        }
+
+        let plaintext = encryption.decrypt(stored).await?;
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| format!(".{}", ext))
+            .unwrap_or_default();
+        let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+        let temp_path = Path::new(&temp_dir).join(format!("readur_plain_{}{}", Uuid::new_v4(), extension));
+        fs::write(&temp_path, &plaintext).await?;
+
+        Ok(PlaintextPath { path: temp_path.to_string_lossy().to_string(), temporary: true })
+    }
+
     pub fn create_document(
         &self,
         filename: &str,
@@ -370,16 +634,40 @@ impl FileService {
     }
 
     pub async fn read_file(&self, file_path: &str) -> Result<Vec<u8>> {
-        // Check if this is a storage backend path (s3:// or other prefixes)
-        if file_path.starts_with("s3://") {
-            // Strip the s3:// prefix and delegate to storage backend
-            let storage_key = file_path.strip_prefix("s3://").unwrap_or(file_path);
-            return self.storage.retrieve_file(storage_key).await;
+        let data = self.read_file_raw(file_path).await?;
+        match &self.encryption {
+            Some(encryption) => encryption.decrypt(data).await,
+            None if encryption::is_encrypted(&data) => {
+                Err(anyhow::anyhow!("{} is encrypted but ENCRYPTION_MASTER_KEY is not set", file_path))
+            }
+            None => Ok(data),
+        }
+    }
+
+    /// The file as stored, still encrypted if it was stored encrypted
+    async fn read_file_raw(&self, file_path: &str) -> Result<Vec<u8>> {
+        match self.read_stored_file(file_path).await {
+            Ok(data) => Ok(data),
+            Err(e) => match storage_migration_service::dual_read_path(file_path).await {
+                // Moved by a storage migration that has not been reconciled yet
+                Some(source_path) => {
+                    warn!("Reading {} failed ({}), using migration source {}", file_path, e, source_path);
+                    self.read_stored_file(&source_path).await
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn read_stored_file(&self, file_path: &str) -> Result<Vec<u8>> {
+        // Object storage paths (s3://, azure://) are read from their backend
+        if is_remote_path(file_path) {
+            return self.backend_for(file_path).retrieve_file(file_path).await;
         }
 
         // For local files, we might need to use the storage backend or fall back to direct file access
         // Try storage backend first, then fall back to legacy file resolution
-        match self.storage.retrieve_file(file_path).await {
+        match self.backend_for(file_path).retrieve_file(file_path).await {
             Ok(data) => Ok(data),
             Err(_) => {
                 // Fall back to legacy file resolution for backward compatibility
@@ -390,8 +678,81 @@ impl FileService {
         }
     }
 
+    /// The file as a stream of chunks, so large downloads are not read into memory.
+    /// Encrypted files are sealed as a whole and are decrypted in memory instead.
+    pub async fn open_stream(&self, file_path: &str) -> Result<FileStream> {
+        if self.encryption.is_some() {
+            return Ok(FileStream::from_bytes(self.read_file(file_path).await?));
+        }
+
+        match self.open_stored_stream(file_path).await {
+            Ok(stream) => Ok(stream),
+            Err(e) => match storage_migration_service::dual_read_path(file_path).await {
+                Some(source_path) => {
+                    warn!("Streaming {} failed ({}), using migration source {}", file_path, e, source_path);
+                    self.open_stored_stream(&source_path).await
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Size of a file as it is served, without reading it. Reads and decrypts
+    /// the file when encryption is enabled.
+    pub async fn file_size(&self, file_path: &str) -> Result<Option<u64>> {
+        Ok(self.open_stream(file_path).await?.size)
+    }
+
+    /// Stream the bytes `start..=end` of a file, for HTTP range requests
+    pub async fn open_range_stream(&self, file_path: &str, start: u64, end: u64) -> Result<FileStream> {
+        if self.encryption.is_some() {
+            return Ok(self.open_stream(file_path).await?.slice(start, end));
+        }
+
+        match self.open_stored_range_stream(file_path, start, end).await {
+            Ok(stream) => Ok(stream),
+            Err(e) => match storage_migration_service::dual_read_path(file_path).await {
+                Some(source_path) => {
+                    warn!("Streaming {} failed ({}), using migration source {}", file_path, e, source_path);
+                    self.open_stored_range_stream(&source_path, start, end).await
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn open_stored_range_stream(&self, file_path: &str, start: u64, end: u64) -> Result<FileStream> {
+        if is_remote_path(file_path) {
+            return self.backend_for(file_path).retrieve_range_stream(file_path, start, end).await;
+        }
+
+        match self.backend_for(file_path).retrieve_range_stream(file_path, start, end).await {
+            Ok(stream) => Ok(stream),
+            Err(_) => Ok(self.open_stored_stream(file_path).await?.slice(start, end)),
+        }
+    }
+
+    async fn open_stored_stream(&self, file_path: &str) -> Result<FileStream> {
+        if is_remote_path(file_path) {
+            return self.backend_for(file_path).retrieve_stream(file_path).await;
+        }
+
+        match self.backend_for(file_path).retrieve_stream(file_path).await {
+            Ok(stream) => Ok(stream),
+            Err(_) => {
+                let resolved_path = self.resolve_file_path(file_path).await?;
+                let file = fs::File::open(&resolved_path).await?;
+                let size = file.metadata().await?.len();
+                Ok(FileStream {
+                    size: Some(size),
+                    chunks: Box::pin(tokio_util::io::ReaderStream::new(file)),
+                })
+            }
+        }
+    }
+
     #[cfg(feature = "ocr")]
-    pub async fn get_or_generate_thumbnail(&self, file_path: &str, filename: &str) -> Result<Vec<u8>> {
+    pub async fn get_or_generate_thumbnail(&self, file_path: &str, filename: &str, mime_type: &str) -> Result<Vec<u8>> {
         // Use the structured thumbnails directory
         let thumbnails_dir = self.get_thumbnails_path();
         if !thumbnails_dir.exists() {
@@ -415,7 +776,7 @@ impl FileService {
 
         // Resolve file path and generate thumbnail
         let resolved_path = self.resolve_file_path(file_path).await?;
-        let thumbnail_data = self.generate_thumbnail(&resolved_path, filename).await?;
+        let thumbnail_data = self.generate_thumbnail(&resolved_path, filename, mime_type).await?;
         
         // Save thumbnail to cache
         fs::write(&thumbnail_path, &thumbnail_data).await?;
@@ -423,16 +784,42 @@ impl FileService {
         Ok(thumbnail_data)
     }
 
+    /// Like [`Self::get_or_generate_thumbnail`], with the first page turned upright by
+    /// `transform`. The cached thumbnail is dropped whenever the transform changes.
     #[cfg(feature = "ocr")]
-    async fn generate_thumbnail(&self, file_path: &str, filename: &str) -> Result<Vec<u8>> {
+    pub async fn get_or_generate_transformed_thumbnail(&self, file_path: &str, filename: &str, mime_type: &str, transform: Option<&crate::models::PageTransform>) -> Result<Vec<u8>> {
+        let Some(transform) = transform.filter(|t| !t.is_identity()) else {
+            return self.get_or_generate_thumbnail(file_path, filename, mime_type).await;
+        };
+
+        let thumbnails_dir = self.get_thumbnails_path();
+        fs::create_dir_all(&thumbnails_dir).await?;
+        let file_stem = Path::new(file_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown");
+        let thumbnail_path = thumbnails_dir.join(format!("{}_thumb.jpg", file_stem));
+        if thumbnail_path.exists() {
+            return self.read_file(&thumbnail_path.to_string_lossy()).await;
+        }
+
+        let resolved_path = self.resolve_file_path(file_path).await?;
+        let thumbnail = image::load_from_memory(&self.generate_thumbnail(&resolved_path, filename, mime_type).await?)?;
+        let upright = crate::ocr::page_transforms::apply_transform(&thumbnail, transform);
+
+        let mut thumbnail_data = Vec::new();
+        DynamicImage::ImageRgb8(upright.to_rgb8()).write_to(&mut std::io::Cursor::new(&mut thumbnail_data), ImageFormat::Jpeg)?;
+        fs::write(&thumbnail_path, &thumbnail_data).await?;
+
+        Ok(thumbnail_data)
+    }
+
+    #[cfg(feature = "ocr")]
+    async fn generate_thumbnail(&self, file_path: &str, filename: &str, mime_type: &str) -> Result<Vec<u8>> {
         let file_data = self.read_file(file_path).await?;
         
-        // Determine file type from extension
-        let extension = Path::new(filename)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("")
-            .to_lowercase();
+        // Determine file type from the detected type, or the extension for types that imply none
+        let extension = crate::mime_detection::rendering_extension(filename, mime_type);
 
         match extension.as_str() {
             "jpg" | "jpeg" | "png" | "bmp" | "tiff" | "gif" => {
@@ -444,8 +831,14 @@ impl FileService {
             "txt" => {
                 self.generate_text_thumbnail(&file_data).await
             }
-            "doc" | "docx" => {
-                self.generate_placeholder_thumbnail("DOC").await
+            ext if office_preview::is_office_extension(ext) => {
+                match self.get_or_generate_preview_pdf(file_path, filename, mime_type).await {
+                    Ok(pdf_data) => self.generate_pdf_thumbnail(&pdf_data).await,
+                    Err(e) => {
+                        warn!("Falling back to placeholder thumbnail for {}: {}", filename, e);
+                        self.generate_placeholder_thumbnail(&extension.to_uppercase()).await
+                    }
+                }
             }
             _ => {
                 // For other file types, generate a placeholder
@@ -481,7 +874,7 @@ impl FileService {
 
     #[cfg(feature = "ocr")]
     async fn generate_pdf_thumbnail(&self, file_data: &[u8]) -> Result<Vec<u8>> {
-        use std::process::Command;
+        use crate::services::converter_sandbox::SandboxedCommand;
         use tokio::fs;
         use uuid::Uuid;
         
@@ -497,14 +890,16 @@ impl FileService {
         }
         
         // Use pdftoppm to convert first page to PNG
-        let output = Command::new("pdftoppm")
+        let output = SandboxedCommand::new("pdftoppm")
             .arg("-f").arg("1")          // First page only
             .arg("-l").arg("1")          // Last page (same as first)
             .arg("-scale-to").arg("200") // Scale to 200px width
             .arg("-png")                 // Output as PNG
             .arg(&temp_pdf_path)
-            .arg(&format!("/tmp/pdf_thumb_{}", temp_id)) // Output prefix
-            .output();
+            .arg(format!("/tmp/pdf_thumb_{}", temp_id)) // Output prefix
+            .input(file_data)
+            .output()
+            .await;
         
         // Clean up temporary PDF file
         let _ = fs::remove_file(&temp_pdf_path).await;
@@ -645,13 +1040,98 @@ impl FileService {
     }
 
     #[cfg(not(feature = "ocr"))]
-    pub async fn get_or_generate_thumbnail(&self, _file_path: &str, _filename: &str) -> Result<Vec<u8>> {
+    pub async fn get_or_generate_thumbnail(&self, _file_path: &str, _filename: &str, _mime_type: &str) -> Result<Vec<u8>> {
         anyhow::bail!("Thumbnail generation requires OCR feature")
     }
 
+    #[cfg(not(feature = "ocr"))]
+    pub async fn get_or_generate_transformed_thumbnail(&self, _file_path: &str, _filename: &str, _mime_type: &str, _transform: Option<&crate::models::PageTransform>) -> Result<Vec<u8>> {
+        anyhow::bail!("Thumbnail generation requires OCR feature")
+    }
+
+    /// Whether a stored document file exists, in the storage backend or at a legacy local path
+    pub async fn file_exists(&self, file_path: &str) -> bool {
+        if self.backend_for(file_path).file_exists(file_path).await.unwrap_or(false) {
+            return true;
+        }
+        !is_remote_path(file_path) && self.resolve_file_path(file_path).await.is_ok()
+    }
+
+    /// PDF rendition of an office document for in-browser preview, converted
+    /// once and cached in the previews directory
+    pub async fn get_or_generate_preview_pdf(&self, file_path: &str, filename: &str, mime_type: &str) -> Result<Vec<u8>> {
+        let extension = crate::mime_detection::rendering_extension(filename, mime_type);
+        let file_stem = Path::new(file_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown");
+        let preview_path = self.get_previews_path().join(format!("{}.pdf", file_stem));
+
+        if let Ok(data) = fs::read(&preview_path).await {
+            return Ok(data);
+        }
+
+        let file_data = self.read_file(file_path).await?;
+        let pdf_data = office_preview::convert_to_pdf(&file_data, &extension).await?;
+
+        fs::create_dir_all(self.get_previews_path()).await?;
+        if let Err(e) = fs::write(&preview_path, &pdf_data).await {
+            warn!("Failed to cache preview {}: {}", preview_path.display(), e);
+        }
+
+        Ok(pdf_data)
+    }
+
+    /// Drop the cached thumbnail and preview of a file so they are regenerated from new content
+    pub async fn invalidate_thumbnail(&self, file_path: &str) {
+        let file_stem = Path::new(file_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown");
+        let cached = [
+            self.get_thumbnails_path().join(format!("{}_thumb.jpg", file_stem)),
+            self.get_previews_path().join(format!("{}.pdf", file_stem)),
+        ];
+        for path in &cached {
+            if let Err(e) = fs::remove_file(path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to remove cached file {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    /// Delete the file of an earlier document version, which is stored under the
+    /// version's id
+    pub async fn delete_version_file(&self, user_id: Uuid, version_id: Uuid, filename: &str, file_path: &str) -> Result<()> {
+        if self.release_blob(file_path).await? {
+            return Ok(());
+        }
+        self.invalidate_thumbnail(file_path).await;
+        self.backend_for(file_path).delete_document_files(user_id, version_id, filename).await
+    }
+
+    /// Delete a file saved with `save_document_file` under an id of its own, such as
+    /// a document's narration
+    pub async fn delete_saved_file(&self, user_id: Uuid, owner_id: Uuid, filename: &str, file_path: &str) -> Result<()> {
+        self.backend_for(file_path).delete_document_files(user_id, owner_id, filename).await
+    }
+
     pub async fn delete_document_files(&self, document: &Document) -> Result<()> {
-        // Use storage backend for deletion - it handles both S3 and local storage
-        match self.storage.delete_document_files(document.user_id, document.id, &document.filename).await {
+        // Shared content goes with its last reference; the backend still removes
+        // the document's own thumbnail and processed image
+        let is_blob = self.release_blob(&document.file_path).await?;
+        if !is_blob {
+            // Cached office previews are not tracked by the storage backend
+            self.invalidate_thumbnail(&document.file_path).await;
+        }
+        // The backend only knows its own layout, not the storage layout template's
+        if self.is_library_path(&document.file_path) {
+            self.backend_for(&document.file_path).delete_stored_file(&document.file_path).await?;
+        }
+
+        // Use the backend the file is stored in - it handles its own layout
+        match self.backend_for(&document.file_path).delete_document_files(document.user_id, document.id, &document.filename).await {
             Ok(_) => {
                 info!("Successfully deleted files for document {} via storage backend", document.id);
                 return Ok(());
@@ -692,9 +1172,11 @@ impl FileService {
         }
 
         // Delete main document file
-        let main_file = Path::new(&document.file_path);
-        if let Some(deleted_path) = safe_delete(&main_file, &mut serious_errors).await {
-            deleted_files.push(deleted_path);
+        if !is_blob {
+            let main_file = Path::new(&document.file_path);
+            if let Some(deleted_path) = safe_delete(&main_file, &mut serious_errors).await {
+                deleted_files.push(deleted_path);
+            }
         }
 
         // Delete thumbnail if it exists
@@ -725,4 +1207,38 @@ impl FileService {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Whether identical document files are stored once, from `STORAGE_DEDUPLICATION`
+pub fn deduplication_enabled() -> bool {
+    std::env::var("STORAGE_DEDUPLICATION")
+        .map(|v| matches!(v.to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Whether a stored path points into object storage rather than the local disk
+fn is_remote_path(file_path: &str) -> bool {
+    file_path.starts_with("s3://") || file_path.starts_with(AZURE_PATH_PREFIX)
+}
+
+/// Path returned by `FileService::plaintext_path`
+pub struct PlaintextPath {
+    path: String,
+    temporary: bool,
+}
+
+impl PlaintextPath {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for PlaintextPath {
+    fn drop(&mut self) {
+        if self.temporary {
+            if let Err(e) = std::fs::remove_file(&self.path) {
+                warn!("Failed to remove decrypted copy {}: {}", self.path, e);
+            }
+        }
+    }
+}
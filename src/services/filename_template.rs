@@ -0,0 +1,238 @@
+use chrono::NaiveDate;
+use regex::{Regex, RegexBuilder};
+use std::path::Path;
+
+use crate::models::ParsedFilename;
+
+/// At most this many templates per source
+pub const MAX_FILENAME_TEMPLATES: usize = 20;
+
+/// Date layouts `{date}` recognizes, tried in order
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y%m%d", "%Y_%m_%d", "%Y.%m.%d", "%d.%m.%Y", "%d-%m-%Y", "%d_%m_%Y"];
+
+/// What `{date}` matches without a format: the layouts of `DATE_FORMATS`
+const DATE_PATTERN: &str = r"\d{4}[-_.]?\d{2}[-_.]?\d{2}|\d{2}[-_.]\d{2}[-_.]\d{4}";
+
+/// A pattern like `{date}_{correspondent}_{title}.pdf` that reads metadata from a file name.
+///
+/// Placeholders are `{date}` (or `{date:<strftime format>}`), `{correspondent}`, `{title}`,
+/// `{document_type}`, `{tag}` (repeatable) and `{*}` for text to skip; everything else must
+/// appear literally, ignoring case. A template with a literal `.` is matched against the whole
+/// file name, one without against the name without its extension.
+#[derive(Debug, Clone)]
+pub struct FilenameTemplate {
+    template: String,
+    regex: Regex,
+    date_format: Option<String>,
+    matches_extension: bool,
+}
+
+impl FilenameTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let template = template.trim();
+        if template.is_empty() {
+            return Err("Filename templates must not be empty".to_string());
+        }
+
+        let mut pattern = String::from("^");
+        let mut date_format = None;
+        let mut tags = 0;
+        let mut placeholders = 0;
+        let mut matches_extension = false;
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            matches_extension |= rest[..start].contains('.');
+            pattern.push_str(&regex::escape(&rest[..start]));
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("Unclosed placeholder in '{}'", template))?;
+            let placeholder = &rest[start + 1..end];
+            let (name, format) = match placeholder.split_once(':') {
+                Some((name, format)) => (name.trim(), Some(format)),
+                None => (placeholder.trim(), None),
+            };
+
+            let group = match name {
+                "date" => {
+                    if date_format.is_some() || pattern.contains("(?P<date>") {
+                        return Err(format!("'{}' has more than one {{date}}", template));
+                    }
+                    match format {
+                        Some(format) if format.trim().is_empty() => {
+                            return Err(format!("Empty date format in '{}'", template));
+                        }
+                        Some(format) => {
+                            date_format = Some(format.to_string());
+                            r"(?P<date>.+?)".to_string()
+                        }
+                        None => format!("(?P<date>{})", DATE_PATTERN),
+                    }
+                }
+                "correspondent" | "title" | "document_type" => {
+                    if pattern.contains(&format!("(?P<{}>", name)) {
+                        return Err(format!("'{}' has more than one {{{}}}", template, name));
+                    }
+                    format!("(?P<{}>.+?)", name)
+                }
+                "tag" => {
+                    tags += 1;
+                    format!("(?P<tag{}>.+?)", tags)
+                }
+                "*" => ".*?".to_string(),
+                _ => return Err(format!("Unknown placeholder {{{}}} in '{}'", placeholder, template)),
+            };
+            if format.is_some() && name != "date" {
+                return Err(format!("Only {{date}} takes a format, not {{{}}}", placeholder));
+            }
+            pattern.push_str(&group);
+            placeholders += 1;
+            rest = &rest[end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(format!("Unopened placeholder in '{}'", template));
+        }
+        matches_extension |= rest.contains('.');
+        pattern.push_str(&regex::escape(rest));
+        pattern.push('$');
+
+        if placeholders == 0 {
+            return Err(format!("'{}' has no placeholders", template));
+        }
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("Invalid template '{}': {}", template, e))?;
+
+        Ok(Self {
+            template: template.to_string(),
+            regex,
+            date_format,
+            matches_extension,
+        })
+    }
+
+    /// The metadata in a file name, or None when the name does not have the template's
+    /// shape or its date is not a date
+    pub fn apply(&self, filename: &str) -> Option<ParsedFilename> {
+        let name = Path::new(filename).file_name().and_then(|name| name.to_str()).unwrap_or(filename);
+        let subject = if self.matches_extension {
+            name
+        } else {
+            Path::new(name).file_stem().and_then(|stem| stem.to_str()).unwrap_or(name)
+        };
+        let captures = self.regex.captures(subject)?;
+
+        let field = |name: &str| {
+            captures
+                .name(name)
+                .map(|value| value.as_str().trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let date = match captures.name("date") {
+            Some(date) => Some(self.parse_date(date.as_str())?),
+            None => None,
+        };
+        let tags = (1..)
+            .map_while(|index| captures.name(&format!("tag{}", index)).map(|_| field(&format!("tag{}", index))))
+            .flatten()
+            .collect();
+
+        Some(ParsedFilename {
+            template: self.template.clone(),
+            date,
+            correspondent: field("correspondent"),
+            title: field("title"),
+            document_type: field("document_type"),
+            tags,
+        })
+    }
+
+    fn parse_date(&self, value: &str) -> Option<NaiveDate> {
+        match &self.date_format {
+            Some(format) => NaiveDate::parse_from_str(value, format).ok(),
+            None => DATE_FORMATS.iter().find_map(|format| NaiveDate::parse_from_str(value, format).ok()),
+        }
+    }
+}
+
+/// Compile a source's templates, failing on the first invalid one
+pub fn compile(templates: &[String]) -> Result<Vec<FilenameTemplate>, String> {
+    if templates.len() > MAX_FILENAME_TEMPLATES {
+        return Err(format!("At most {} filename templates per source", MAX_FILENAME_TEMPLATES));
+    }
+    templates.iter().map(|template| FilenameTemplate::parse(template)).collect()
+}
+
+/// The metadata of the first template that matches the file name
+pub fn parse_filename(templates: &[FilenameTemplate], filename: &str) -> Option<ParsedFilename> {
+    templates.iter().find_map(|template| template.apply(filename))
+}
+
+/// The name a document gets from a parsed title: the title with the original's extension
+pub fn title_filename(title: &str, original_filename: &str) -> String {
+    let title: String = title.chars().map(|c| if c == '/' || c == '\\' { '-' } else { c }).collect();
+    match Path::new(original_filename).extension().and_then(|ext| ext.to_str()) {
+        Some(extension) if !title.to_lowercase().ends_with(&format!(".{}", extension.to_lowercase())) => {
+            format!("{}.{}", title, extension)
+        }
+        _ => title,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_correspondent_title() {
+        let template = FilenameTemplate::parse("{date}_{correspondent}_{title}.pdf").unwrap();
+        let parsed = template.apply("2024-03-15_ACME_Invoice March.PDF").unwrap();
+
+        assert_eq!(parsed.date, NaiveDate::from_ymd_opt(2024, 3, 15));
+        assert_eq!(parsed.correspondent.as_deref(), Some("ACME"));
+        assert_eq!(parsed.title.as_deref(), Some("Invoice March"));
+        assert!(template.apply("2024-03-15_ACME_Invoice.docx").is_none());
+        assert!(template.apply("2024-13-45_ACME_Invoice.pdf").is_none());
+    }
+
+    #[test]
+    fn test_templates_without_extension_match_the_stem() {
+        let template = FilenameTemplate::parse("{document_type} {date:%d.%m.%y} {tag} {tag}").unwrap();
+        let parsed = template.apply("/scans/Receipt 01.02.24 tax 2024.jpg").unwrap();
+
+        assert_eq!(parsed.document_type.as_deref(), Some("Receipt"));
+        assert_eq!(parsed.date, NaiveDate::from_ymd_opt(2024, 2, 1));
+        assert_eq!(parsed.tags, vec!["tax".to_string(), "2024".to_string()]);
+    }
+
+    #[test]
+    fn test_first_matching_template_wins() {
+        let templates = compile(&["{date}-{title}".to_string(), "{*}_{title}".to_string()]).unwrap();
+
+        let parsed = parse_filename(&templates, "scan_contract.pdf").unwrap();
+        assert_eq!(parsed.template, "{*}_{title}");
+        assert_eq!(parsed.title.as_deref(), Some("contract"));
+        assert!(parse_filename(&templates, "contract.pdf").is_none());
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(FilenameTemplate::parse("").is_err());
+        assert!(FilenameTemplate::parse("invoice.pdf").is_err());
+        assert!(FilenameTemplate::parse("{date}_{owner}").is_err());
+        assert!(FilenameTemplate::parse("{title}_{title}").is_err());
+        assert!(FilenameTemplate::parse("{date}_{date}").is_err());
+        assert!(FilenameTemplate::parse("{title:%Y}").is_err());
+        assert!(FilenameTemplate::parse("{title").is_err());
+        assert!(FilenameTemplate::parse("title}").is_err());
+    }
+
+    #[test]
+    fn test_title_filename_keeps_extension() {
+        assert_eq!(title_filename("Invoice March", "2024_ACME_Invoice March.pdf"), "Invoice March.pdf");
+        assert_eq!(title_filename("a/b", "x.PDF"), "a-b.PDF");
+    }
+}
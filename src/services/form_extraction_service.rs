@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use tracing::info;
+
+use crate::db::Database;
+use crate::models::{Document, DocumentFormFields, FormTemplateField};
+use crate::ocr::form_fields::{self, FormField};
+
+/// Minimum key overlap (Jaccard) for a document to reuse a learned template
+const TEMPLATE_MATCH_THRESHOLD: f32 = 0.5;
+/// Forms with fewer detected fields are not remembered as templates
+const MIN_TEMPLATE_FIELDS: usize = 3;
+const MAX_TEMPLATE_NAME_CHARS: usize = 60;
+
+/// Extracts label/value pairs from form documents into a key-value map,
+/// remembering recurring layouts so later documents of the same form yield the same keys
+pub struct FormExtractionService {
+    db: Database,
+}
+
+impl FormExtractionService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Whether form extraction should run automatically after OCR
+    pub fn enabled_after_ocr() -> bool {
+        std::env::var("FORMS_EXTRACTION_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false)
+    }
+
+    pub async fn extract_for_document(&self, document: &Document) -> Result<DocumentFormFields> {
+        let text = document
+            .ocr_text
+            .as_deref()
+            .or(document.content.as_deref())
+            .filter(|t| !t.trim().is_empty())
+            .ok_or_else(|| anyhow!("Document {} has no text to extract form fields from", document.id))?;
+
+        let detected = form_fields::extract_fields(text);
+        let detected_keys: BTreeSet<String> = detected.iter().map(|f| f.key.clone()).collect();
+
+        let templates = self.db.get_form_templates(document.user_id).await?;
+        let best_match = templates
+            .iter()
+            .map(|t| {
+                let keys: BTreeSet<String> = t.fields.iter().map(|f| f.key.clone()).collect();
+                (t, form_fields::key_overlap(&detected_keys, &keys))
+            })
+            .filter(|(_, score)| *score >= TEMPLATE_MATCH_THRESHOLD)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let (template_id, fields) = match best_match {
+            Some((template, score)) => {
+                let (fields, template_fields) = apply_template(text, &detected, &template.fields);
+                self.db.record_form_template_match(template.id, &template_fields).await?;
+                info!("Document {} matched form template '{}' ({:.0}% key overlap)", document.id, template.name, score * 100.0);
+                (Some(template.id), fields)
+            }
+            None if detected.len() >= MIN_TEMPLATE_FIELDS => {
+                let template_fields: Vec<FormTemplateField> = detected
+                    .iter()
+                    .map(|f| FormTemplateField { key: f.key.clone(), label: f.label.clone() })
+                    .collect();
+                let template = self
+                    .db
+                    .create_form_template(document.user_id, &template_name(text, &document.original_filename), &template_fields)
+                    .await?;
+                info!("Learned form template '{}' from document {}", template.name, document.id);
+                (Some(template.id), to_map(&detected))
+            }
+            None => (None, to_map(&detected)),
+        };
+
+        self.db.upsert_document_form_fields(document.id, template_id, &fields).await
+    }
+}
+
+/// Fill the template's fields in order, looking up labels the generic pass missed.
+/// Returns the value map and the template's field list extended with newly seen fields.
+fn apply_template(
+    text: &str,
+    detected: &[FormField],
+    template_fields: &[FormTemplateField],
+) -> (Map<String, Value>, Vec<FormTemplateField>) {
+    let mut fields = Map::new();
+    let mut merged = template_fields.to_vec();
+
+    for field in template_fields {
+        let value = detected
+            .iter()
+            .find(|d| d.key == field.key)
+            .map(|d| d.value.clone())
+            .or_else(|| form_fields::find_value_for_label(text, &field.label));
+        fields.insert(field.key.clone(), value.map(Value::String).unwrap_or(Value::Null));
+    }
+
+    for field in detected {
+        if !fields.contains_key(&field.key) {
+            fields.insert(field.key.clone(), Value::String(field.value.clone()));
+            merged.push(FormTemplateField { key: field.key.clone(), label: field.label.clone() });
+        }
+    }
+
+    (fields, merged)
+}
+
+fn to_map(detected: &[FormField]) -> Map<String, Value> {
+    detected
+        .iter()
+        .map(|f| (f.key.clone(), Value::String(f.value.clone())))
+        .collect()
+}
+
+/// Forms usually carry their title on the first line
+fn template_name(text: &str, filename: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .find(|line| line.chars().any(char::is_alphabetic))
+        .unwrap_or(filename)
+        .chars()
+        .take(MAX_TEMPLATE_NAME_CHARS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_template_keeps_template_keys_and_learns_new_ones() {
+        let text = "Name: Jane Doe\nReference\nAB-123\nPhone: 555-0100";
+        let detected = form_fields::extract_fields(text);
+        let template = vec![
+            FormTemplateField { key: "name".to_string(), label: "Name".to_string() },
+            FormTemplateField { key: "reference".to_string(), label: "Reference".to_string() },
+            FormTemplateField { key: "signature_date".to_string(), label: "Signature Date".to_string() },
+        ];
+
+        let (fields, merged) = apply_template(text, &detected, &template);
+
+        assert_eq!(fields["name"], Value::String("Jane Doe".to_string()));
+        assert_eq!(fields["reference"], Value::String("AB-123".to_string()));
+        assert_eq!(fields["signature_date"], Value::Null);
+        assert_eq!(fields["phone"], Value::String("555-0100".to_string()));
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged[3].key, "phone");
+    }
+}
@@ -0,0 +1,566 @@
+//! A minimal FTP server (RFC 959, with EPSV and EPRT from RFC 2428) that only accepts
+//! uploads, for scanners and multifunction printers that can scan to FTP but not to
+//! HTTPS. Each device signs in with credentials of its own; what it stores is ingested
+//! like an upload by the device's owner and queued for OCR.
+//!
+//! Listings are always empty and nothing can be downloaded, renamed or deleted. FTP
+//! sends credentials in the clear, so the drop belongs on a trusted network.
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use rand::{rngs::OsRng, Rng};
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tracing::{error, info, warn};
+
+use crate::auth::AuthUser;
+use crate::models::ScanDevice;
+use crate::routes::documents::crud::{ingest_upload, DocumentError, UploadedFile};
+use crate::services::audit_service::ClientInfo;
+use crate::AppState;
+
+const DEFAULT_PASSIVE_PORTS: RangeInclusive<u16> = 30000..=30009;
+/// Connections served at once
+const MAX_SESSIONS: usize = 32;
+/// Idle time after which a control connection is closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Time a client has to open the data connection
+const DATA_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest a single upload may take
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(1800);
+const MAX_COMMAND_LENGTH: u64 = 1024;
+/// Failed logins before the connection is closed
+const MAX_FAILED_LOGINS: u32 = 3;
+const FAILED_LOGIN_DELAY: Duration = Duration::from_secs(2);
+
+/// Characters of generated passwords: lowercase letters and digits that are hard to
+/// confuse, since they are often typed on a printer's touch screen
+const PASSWORD_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const PASSWORD_LENGTH: usize = 20;
+
+static FTP_DROP_CONFIG: Lazy<Result<Option<FtpDropConfig>, String>> =
+    Lazy::new(|| FtpDropConfig::from_env().map_err(|e| e.to_string()));
+
+/// The scan drop configuration from the environment: None when the drop is off, an
+/// error when `FTP_DROP_ADDRESS` is set but unusable
+pub fn configured() -> Result<Option<FtpDropConfig>, String> {
+    FTP_DROP_CONFIG.clone()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FtpDropConfig {
+    /// Where the control connection listens
+    pub address: SocketAddr,
+    /// Ports passive data connections are opened on
+    pub passive_ports: RangeInclusive<u16>,
+    /// Address announced for passive connections, when clients reach the server under
+    /// another one than it sees, e.g. behind NAT or in a container
+    pub passive_address: Option<Ipv4Addr>,
+}
+
+impl FtpDropConfig {
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(address) = env_value("FTP_DROP_ADDRESS") else {
+            return Ok(None);
+        };
+        let address = address
+            .parse()
+            .map_err(|_| anyhow!("FTP_DROP_ADDRESS '{}' is not an address like 0.0.0.0:2121", address))?;
+        let passive_ports = match env_value("FTP_DROP_PASSIVE_PORTS") {
+            Some(ports) => parse_port_range(&ports)
+                .ok_or_else(|| anyhow!("FTP_DROP_PASSIVE_PORTS '{}' is not a range like 30000-30009", ports))?,
+            None => DEFAULT_PASSIVE_PORTS,
+        };
+        let passive_address = match env_value("FTP_DROP_PASSIVE_ADDRESS") {
+            Some(ip) => Some(ip.parse().map_err(|_| anyhow!("FTP_DROP_PASSIVE_ADDRESS '{}' is not an IPv4 address", ip))?),
+            None => None,
+        };
+
+        Ok(Some(Self { address, passive_ports, passive_address }))
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// `30000-30009`, or a single port
+fn parse_port_range(value: &str) -> Option<RangeInclusive<u16>> {
+    let (start, end) = value.split_once('-').unwrap_or((value, value));
+    let (start, end): (u16, u16) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+    (start > 0 && start <= end).then_some(start..=end)
+}
+
+/// A new username and password for a device
+pub fn generate_credentials() -> (String, String) {
+    let mut rng = OsRng;
+    let username = format!("scan-{:08x}", rng.gen::<u32>());
+    let password = (0..PASSWORD_LENGTH)
+        .map(|_| PASSWORD_ALPHABET[rng.gen_range(0..PASSWORD_ALPHABET.len())] as char)
+        .collect();
+    (username, password)
+}
+
+/// Generated passwords are long and random, so a plain hash protects them like API tokens
+pub fn hash_password(password: &str) -> String {
+    format!("{:x}", Sha256::digest(password.as_bytes()))
+}
+
+/// The scan drop address as shown to users configuring a device
+pub fn advertised_address(config: &FtpDropConfig) -> String {
+    match config.passive_address {
+        Some(ip) => SocketAddr::new(IpAddr::V4(ip), config.address.port()).to_string(),
+        None => config.address.to_string(),
+    }
+}
+
+pub struct FtpDropService {
+    state: Arc<AppState>,
+    config: Arc<FtpDropConfig>,
+}
+
+impl FtpDropService {
+    pub fn new(state: Arc<AppState>, config: FtpDropConfig) -> Self {
+        Self { state, config: Arc::new(config) }
+    }
+
+    /// Accept connections until the process ends
+    pub async fn run(self) {
+        let listener = match TcpListener::bind(self.config.address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("FTP scan drop cannot listen on {}: {}", self.config.address, e);
+                return;
+            }
+        };
+        info!("FTP scan drop listening on {}", self.config.address);
+
+        let sessions = Arc::new(Semaphore::new(MAX_SESSIONS));
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    warn!("FTP scan drop failed to accept a connection: {}", e);
+                    continue;
+                }
+            };
+            let Ok(permit) = sessions.clone().try_acquire_owned() else {
+                let mut stream = stream;
+                let _ = stream.write_all(b"421 Too many connections, try again later\r\n").await;
+                continue;
+            };
+            let state = self.state.clone();
+            let config = self.config.clone();
+            tokio::spawn(async move {
+                if let Err(e) = Session::new(state, config, stream, peer).serve().await {
+                    warn!("FTP scan drop session with {} ended: {}", peer, e);
+                }
+                drop(permit);
+            });
+        }
+    }
+}
+
+/// Where the next data connection comes from
+enum DataChannel {
+    /// PASV or EPSV: the client connects to this listener
+    Passive(TcpListener),
+    /// PORT or EPRT: the server connects to the client
+    Active(SocketAddr),
+}
+
+/// What a transfer ended with, as a reply
+type Reply = (u16, String);
+
+struct Session {
+    state: Arc<AppState>,
+    config: Arc<FtpDropConfig>,
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    peer: SocketAddr,
+    local: SocketAddr,
+    pending_user: Option<String>,
+    login: Option<(ScanDevice, AuthUser)>,
+    failed_logins: u32,
+    data: Option<DataChannel>,
+}
+
+impl Session {
+    fn new(state: Arc<AppState>, config: Arc<FtpDropConfig>, stream: TcpStream, peer: SocketAddr) -> Self {
+        let local = stream.local_addr().unwrap_or(config.address);
+        let (reader, writer) = stream.into_split();
+        Self {
+            state,
+            config,
+            reader: BufReader::new(reader),
+            writer,
+            peer,
+            local,
+            pending_user: None,
+            login: None,
+            failed_logins: 0,
+            data: None,
+        }
+    }
+
+    async fn reply(&mut self, code: u16, message: &str) -> Result<()> {
+        self.writer.write_all(format!("{} {}\r\n", code, message).as_bytes()).await?;
+        Ok(())
+    }
+
+    async fn serve(mut self) -> Result<()> {
+        self.reply(220, "Readur scan drop ready").await?;
+        loop {
+            let line = match tokio::time::timeout(IDLE_TIMEOUT, read_command(&mut self.reader)).await {
+                Ok(Ok(Some(line))) => line,
+                Ok(Ok(None)) => return Ok(()),
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => {
+                    self.reply(421, "Idle timeout, closing connection").await?;
+                    return Ok(());
+                }
+            };
+            let (verb, argument) = parse_command(&line);
+
+            if self.login.is_none() && !matches!(verb.as_str(), "USER" | "PASS" | "QUIT" | "SYST" | "FEAT" | "OPTS" | "NOOP" | "AUTH") {
+                self.reply(530, "Please log in with USER and PASS").await?;
+                continue;
+            }
+
+            match verb.as_str() {
+                "USER" => {
+                    self.pending_user = Some(argument.to_string());
+                    self.login = None;
+                    self.reply(331, "Password required").await?;
+                }
+                "PASS" => {
+                    if !self.log_in(argument).await? {
+                        return Ok(());
+                    }
+                }
+                "QUIT" => {
+                    self.reply(221, "Goodbye").await?;
+                    return Ok(());
+                }
+                "SYST" => self.reply(215, "UNIX Type: L8").await?,
+                "FEAT" => {
+                    self.writer
+                        .write_all(b"211-Features:\r\n EPSV\r\n EPRT\r\n PASV\r\n UTF8\r\n211 End\r\n")
+                        .await?;
+                }
+                "OPTS" => self.reply(200, "OK").await?,
+                "NOOP" => self.reply(200, "OK").await?,
+                "AUTH" => self.reply(502, "TLS is not supported").await?,
+                "PWD" | "XPWD" => self.reply(257, "\"/\" is the current directory").await?,
+                // Devices often change into a folder first; every folder is the drop
+                "CWD" | "XCWD" | "CDUP" | "XCUP" => self.reply(250, "Directory changed").await?,
+                "MKD" | "XMKD" => self.reply(257, "Directory created").await?,
+                "TYPE" | "MODE" | "STRU" => self.reply(200, "OK").await?,
+                "ALLO" => self.reply(202, "No storage allocation necessary").await?,
+                "PASV" => self.passive(false).await?,
+                "EPSV" => self.passive(true).await?,
+                "PORT" | "EPRT" => {
+                    let address = if verb == "PORT" { parse_port(argument) } else { parse_eprt(argument) };
+                    // Data connections only go back to the client, which rules out bounce attacks
+                    match address.filter(|address| address.ip() == self.peer.ip() && address.port() >= 1024) {
+                        Some(address) => {
+                            self.data = Some(DataChannel::Active(address));
+                            self.reply(200, "Data connection address accepted").await?;
+                        }
+                        None => self.reply(501, "Data connections go to your own address only").await?,
+                    }
+                }
+                "LIST" | "NLST" | "MLSD" => self.empty_listing().await?,
+                "STOR" | "STOU" | "APPE" => self.store(argument).await?,
+                "SIZE" | "MDTM" | "RETR" => self.reply(550, "Files cannot be read back from the scan drop").await?,
+                "DELE" | "RMD" | "XRMD" | "RNFR" | "RNTO" => self.reply(550, "The scan drop only accepts uploads").await?,
+                "ABOR" => self.reply(226, "No transfer to abort").await?,
+                _ => self.reply(502, "Command not implemented").await?,
+            }
+        }
+    }
+
+    /// Check a device's password; false when the connection should close
+    async fn log_in(&mut self, password: &str) -> Result<bool> {
+        let Some(username) = self.pending_user.take() else {
+            self.reply(503, "Log in with USER first").await?;
+            return Ok(true);
+        };
+
+        let login = self
+            .state
+            .db
+            .get_scan_device_login(&username)
+            .await?
+            .filter(|(device, password_hash)| device.enabled && *password_hash == hash_password(password));
+        let user = match &login {
+            Some((device, _)) => self.state.db.get_user_by_id(device.user_id).await?,
+            None => None,
+        };
+
+        match (login, user) {
+            (Some((device, _)), Some(user)) => {
+                if let Err(e) = self.state.db.touch_scan_device(device.id, &self.peer.ip().to_string()).await {
+                    warn!("Failed to record login of scan device {}: {}", device.id, e);
+                }
+                info!("Scan device '{}' of user {} logged in from {}", device.name, user.id, self.peer);
+                self.login = Some((device, AuthUser { user, workspace_id: None }));
+                self.reply(230, "Logged in, ready for scans").await?;
+                Ok(true)
+            }
+            _ => {
+                self.failed_logins += 1;
+                warn!("Failed FTP scan drop login as '{}' from {}", username, self.peer);
+                tokio::time::sleep(FAILED_LOGIN_DELAY).await;
+                if self.failed_logins >= MAX_FAILED_LOGINS {
+                    self.reply(421, "Too many failed logins").await?;
+                    return Ok(false);
+                }
+                self.reply(530, "Login incorrect").await?;
+                Ok(true)
+            }
+        }
+    }
+
+    async fn passive(&mut self, extended: bool) -> Result<()> {
+        let ip = self.config.passive_address.map(IpAddr::V4).unwrap_or(self.local.ip());
+        let announced = match ip {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(ip) => ip.to_ipv4_mapped(),
+        };
+        if !extended && announced.is_none() {
+            return self.reply(425, "Use EPSV on IPv6").await;
+        }
+
+        let Some(listener) = self.bind_passive().await else {
+            return self.reply(425, "No passive port is free").await;
+        };
+        let port = listener.local_addr()?.port();
+        self.data = Some(DataChannel::Passive(listener));
+        if extended {
+            self.reply(229, &format!("Entering Extended Passive Mode (|||{}|)", port)).await
+        } else {
+            self.reply(227, &pasv_reply(announced.unwrap_or(Ipv4Addr::UNSPECIFIED), port)).await
+        }
+    }
+
+    /// A listener on a free passive port, starting from a random one
+    async fn bind_passive(&self) -> Option<TcpListener> {
+        let (start, end) = (*self.config.passive_ports.start(), *self.config.passive_ports.end());
+        let count = (end - start) as u32 + 1;
+        let offset = OsRng.gen_range(0..count);
+        let unspecified = match self.local.ip() {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
+        for i in 0..count {
+            let port = start + ((offset + i) % count) as u16;
+            if let Ok(listener) = TcpListener::bind(SocketAddr::new(unspecified, port)).await {
+                return Some(listener);
+            }
+        }
+        None
+    }
+
+    /// Open the data connection set up by PASV, EPSV, PORT or EPRT
+    async fn open_data(&mut self) -> Result<Option<TcpStream>> {
+        let stream = match self.data.take() {
+            Some(DataChannel::Passive(listener)) => {
+                match tokio::time::timeout(DATA_CONNECT_TIMEOUT, listener.accept()).await {
+                    // Only the client may use the port it asked for
+                    Ok(Ok((stream, address))) if address.ip() == self.peer.ip() => Some(stream),
+                    Ok(Ok((_, address))) => {
+                        warn!("FTP scan drop refused a data connection from {} for {}", address, self.peer);
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            Some(DataChannel::Active(address)) => {
+                tokio::time::timeout(DATA_CONNECT_TIMEOUT, TcpStream::connect(address)).await.ok().and_then(Result::ok)
+            }
+            None => {
+                self.reply(425, "Use PASV or PORT first").await?;
+                return Ok(None);
+            }
+        };
+        if stream.is_none() {
+            self.reply(425, "Cannot open data connection").await?;
+        }
+        Ok(stream)
+    }
+
+    async fn empty_listing(&mut self) -> Result<()> {
+        let Some(mut stream) = self.open_data().await? else {
+            return Ok(());
+        };
+        self.reply(150, "Opening data connection for directory listing").await?;
+        stream.shutdown().await.ok();
+        self.reply(226, "Listing complete").await
+    }
+
+    async fn store(&mut self, argument: &str) -> Result<()> {
+        let filename = upload_filename(argument);
+        let Some(mut stream) = self.open_data().await? else {
+            return Ok(());
+        };
+        self.reply(150, "Ready to receive").await?;
+
+        let max_size = self.state.config.max_file_size_mb * 1024 * 1024;
+        let mut data = Vec::new();
+        let received = tokio::time::timeout(TRANSFER_TIMEOUT, (&mut stream).take(max_size + 1).read_to_end(&mut data)).await;
+        drop(stream);
+        match received {
+            Ok(Ok(_)) if data.len() as u64 > max_size => {
+                return self
+                    .reply(552, &format!("Files may be at most {}MB", self.state.config.max_file_size_mb))
+                    .await;
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return self.reply(426, &format!("Transfer aborted: {}", e)).await,
+            Err(_) => return self.reply(426, "Transfer timed out").await,
+        }
+        if data.is_empty() {
+            return self.reply(226, "Empty file ignored").await;
+        }
+
+        let (code, message) = self.ingest(filename, data).await;
+        self.reply(code, &message).await
+    }
+
+    async fn ingest(&self, filename: String, data: Vec<u8>) -> Reply {
+        let Some((device, auth_user)) = &self.login else {
+            return (530, "Not logged in".to_string());
+        };
+        let client = ClientInfo {
+            ip_address: Some(self.peer.ip().to_string()),
+            user_agent: Some(format!("FTP scan drop: {}", device.name)),
+            ..Default::default()
+        };
+        let file = UploadedFile { filename: filename.clone(), content_type: "application/octet-stream".to_string(), data };
+
+        match ingest_upload(&self.state, auth_user, &client, file, None, Vec::new()).await {
+            Ok(uploaded) => {
+                info!("Scan device '{}' delivered {} as document {}", device.name, filename, uploaded.id);
+                if let Err(e) = self.state.db.count_scan_device_document(device.id).await {
+                    warn!("Failed to count document of scan device {}: {}", device.id, e);
+                }
+                (226, "Transfer complete".to_string())
+            }
+            // Scanning the same page twice is not the device's mistake
+            Err(DocumentError::Conflict(_)) => (226, "Transfer complete, already in the library".to_string()),
+            Err(DocumentError::PayloadTooLarge(message)) | Err(DocumentError::QuotaExceeded(message)) => (552, message),
+            Err(DocumentError::MalwareDetected(message))
+            | Err(DocumentError::UnsupportedMediaType(message))
+            | Err(DocumentError::RejectedByHook(message))
+            | Err(DocumentError::Forbidden(message)) => (550, message),
+            Err(e) => {
+                error!("Failed to ingest {} from scan device {}: {:?}", filename, device.id, e);
+                (451, "The scan could not be stored, try again later".to_string())
+            }
+        }
+    }
+}
+
+async fn read_command(reader: &mut BufReader<OwnedReadHalf>) -> std::io::Result<Option<String>> {
+    let mut line = Vec::new();
+    if (&mut *reader).take(MAX_COMMAND_LENGTH).read_until(b'\n', &mut line).await? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string()))
+}
+
+/// The command verb in upper case and its argument
+fn parse_command(line: &str) -> (String, &str) {
+    let line = line.trim_start();
+    match line.split_once(' ') {
+        Some((verb, argument)) => (verb.to_ascii_uppercase(), argument.trim()),
+        None => (line.to_ascii_uppercase(), ""),
+    }
+}
+
+/// The file name of a STOR path, without the folders devices put it in
+fn upload_filename(argument: &str) -> String {
+    let name = argument.rsplit(['/', '\\']).next().unwrap_or_default().trim();
+    if name.is_empty() || name == "." || name == ".." {
+        format!("scan-{}.pdf", chrono::Utc::now().format("%Y%m%d-%H%M%S"))
+    } else {
+        name.to_string()
+    }
+}
+
+/// `h1,h2,h3,h4,p1,p2` of a PORT command
+fn parse_port(argument: &str) -> Option<SocketAddr> {
+    let numbers = argument.split(',').map(|n| n.trim().parse::<u8>().ok()).collect::<Option<Vec<_>>>()?;
+    let [a, b, c, d, p1, p2] = numbers[..] else {
+        return None;
+    };
+    Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(a, b, c, d)), u16::from(p1) << 8 | u16::from(p2)))
+}
+
+/// `|1|ip|port|` or `|2|ip|port|` of an EPRT command
+fn parse_eprt(argument: &str) -> Option<SocketAddr> {
+    let delimiter = argument.chars().next()?;
+    let parts: Vec<&str> = argument.split(delimiter).collect();
+    let [_, protocol, ip, port, _] = parts[..] else {
+        return None;
+    };
+    let ip: IpAddr = ip.parse().ok()?;
+    match (protocol, ip) {
+        ("1", IpAddr::V4(_)) | ("2", IpAddr::V6(_)) => Some(SocketAddr::new(ip, port.parse().ok()?)),
+        _ => None,
+    }
+}
+
+fn pasv_reply(ip: Ipv4Addr, port: u16) -> String {
+    let [a, b, c, d] = ip.octets();
+    format!("Entering Passive Mode ({},{},{},{},{},{})", a, b, c, d, port >> 8, port & 0xff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse_command("stor scans/Scan 001.pdf"), ("STOR".to_string(), "scans/Scan 001.pdf"));
+        assert_eq!(parse_command("PASV"), ("PASV".to_string(), ""));
+        assert_eq!(upload_filename("/scans/2025/Scan 001.pdf"), "Scan 001.pdf");
+        assert!(upload_filename("../").starts_with("scan-"));
+    }
+
+    #[test]
+    fn test_data_connection_addresses() {
+        assert_eq!(parse_port("192,168,1,20,117,49"), Some("192.168.1.20:30001".parse().unwrap()));
+        assert_eq!(parse_port("192,168,1,20,117"), None);
+        assert_eq!(parse_port("300,168,1,20,117,49"), None);
+        assert_eq!(parse_eprt("|1|192.168.1.20|30001|"), Some("192.168.1.20:30001".parse().unwrap()));
+        assert_eq!(parse_eprt("|2|::1|30001|"), Some("[::1]:30001".parse().unwrap()));
+        assert_eq!(parse_eprt("|1|::1|30001|"), None);
+        assert_eq!(pasv_reply(Ipv4Addr::new(192, 168, 1, 5), 30001), "Entering Passive Mode (192,168,1,5,117,49)");
+    }
+
+    #[test]
+    fn test_passive_port_ranges() {
+        assert_eq!(parse_port_range("30000-30009"), Some(30000..=30009));
+        assert_eq!(parse_port_range("2120"), Some(2120..=2120));
+        assert_eq!(parse_port_range("30009-30000"), None);
+        assert_eq!(parse_port_range("ports"), None);
+    }
+
+    #[test]
+    fn test_generated_credentials() {
+        let (username, password) = generate_credentials();
+        assert!(username.starts_with("scan-") && username.len() == 13);
+        assert_eq!(password.len(), PASSWORD_LENGTH);
+        assert!(password.bytes().all(|b| PASSWORD_ALPHABET.contains(&b)));
+        assert_eq!(hash_password(&password), hash_password(&password));
+    }
+}
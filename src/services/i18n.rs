@@ -0,0 +1,364 @@
+//! Server-generated text in the reader's language: notifications, email digests and the
+//! error messages returned to the web interface.
+//!
+//! Texts come from catalogs, flat JSON objects mapping keys to `{{name}}` templates, one
+//! per locale. English and German are built in. `I18N_CATALOG_DIR` holds `<locale>.json`
+//! files that add locales or replace built-in texts; they are read at startup and again
+//! on `POST /api/i18n/reload`. A text missing from a locale is taken from its language
+//! (`de` for `de-AT`), then from the instance's default language or `DEFAULT_LOCALE`,
+//! then from English.
+//!
+//! Notifications and digests are written in their recipient's locale. Error messages
+//! follow the locale of the request: `Accept-Language` at first, replaced by the
+//! signed-in user's once the request has been authenticated.
+
+use axum::{extract::Request, http::header, middleware::Next, response::Response};
+use once_cell::sync::Lazy;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{CreateNotification, LocaleInfo};
+
+/// Locale of last resort, complete by definition
+pub const FALLBACK_LOCALE: &str = "en";
+
+const BUILT_IN_CATALOGS: [(&str, &str); 2] = [
+    ("en", include_str!("../../locales/en.json")),
+    ("de", include_str!("../../locales/de.json")),
+];
+
+/// How long a user's locale is trusted before it is read again, so a change made on
+/// another instance shows up
+const USER_LOCALE_TTL: Duration = Duration::from_secs(60);
+const MAX_CACHED_USER_LOCALES: usize = 10_000;
+
+type Catalog = HashMap<String, String>;
+
+static CATALOGS: Lazy<RwLock<BTreeMap<String, Catalog>>> = Lazy::new(|| {
+    let (catalogs, errors) = load_catalogs();
+    for error in errors {
+        warn!("Skipped translation catalog: {}", error);
+    }
+    RwLock::new(catalogs)
+});
+
+/// The default language of the instance settings, which comes before `DEFAULT_LOCALE`.
+/// Set at startup and when an admin changes it.
+static INSTANCE_LOCALE: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+
+static USER_LOCALES: Lazy<Mutex<HashMap<Uuid, (Instant, Option<String>)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+tokio::task_local! {
+    static LOCALE: RefCell<String>;
+}
+
+/// `de`, `de-AT` and `de_at` alike become `de` or `de-AT`; None for anything that is not
+/// a language with an optional region
+pub fn normalize_locale(code: &str) -> Option<String> {
+    let mut parts = code.trim().split(['-', '_']);
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let language = language.to_ascii_lowercase();
+    match (parts.next(), parts.next()) {
+        (None, _) => Some(language),
+        (Some(region), None)
+            if (region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()))
+                || (region.len() == 3 && region.chars().all(|c| c.is_ascii_digit())) =>
+        {
+            Some(format!("{}-{}", language, region.to_ascii_uppercase()))
+        }
+        _ => None,
+    }
+}
+
+fn parse_catalog(json: &str) -> Result<Catalog, String> {
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+/// The built-in catalogs with those of `I18N_CATALOG_DIR` merged in, and why files
+/// were skipped
+fn load_catalogs() -> (BTreeMap<String, Catalog>, Vec<String>) {
+    let mut catalogs: BTreeMap<String, Catalog> = BUILT_IN_CATALOGS
+        .iter()
+        .map(|(locale, json)| (locale.to_string(), parse_catalog(json).expect("built-in catalogs are valid")))
+        .collect();
+    let mut errors = Vec::new();
+
+    let Some(dir) = std::env::var("I18N_CATALOG_DIR").ok().filter(|v| !v.trim().is_empty()) else {
+        return (catalogs, errors);
+    };
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            errors.push(format!("{}: {}", dir, e));
+            return (catalogs, errors);
+        }
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    for path in paths {
+        let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()).and_then(normalize_locale) else {
+            errors.push(format!("{}: the file name is not a locale such as de or pt-BR", path.display()));
+            continue;
+        };
+        match std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|json| parse_catalog(&json)) {
+            Ok(catalog) => catalogs.entry(locale).or_default().extend(catalog),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    (catalogs, errors)
+}
+
+/// Read the catalogs again. Returns why files were skipped; the rest are used.
+pub fn reload_catalogs() -> Vec<String> {
+    let (catalogs, errors) = load_catalogs();
+    for error in &errors {
+        warn!("Skipped translation catalog: {}", error);
+    }
+    info!("Loaded translation catalogs for {}", catalogs.keys().cloned().collect::<Vec<_>>().join(", "));
+    *CATALOGS.write().unwrap_or_else(|e| e.into_inner()) = catalogs;
+    errors
+}
+
+/// The locales there are catalogs for, with how many English texts each lacks
+pub fn available_locales() -> Vec<LocaleInfo> {
+    let catalogs = CATALOGS.read().unwrap_or_else(|e| e.into_inner());
+    let english = catalogs.get(FALLBACK_LOCALE);
+    catalogs
+        .iter()
+        .map(|(code, catalog)| LocaleInfo {
+            code: code.clone(),
+            name: catalog.get("locale.name").cloned().unwrap_or_else(|| code.clone()),
+            keys: catalog.len(),
+            missing_keys: english.map_or(0, |english| english.keys().filter(|key| !catalog.contains_key(*key)).count()),
+        })
+        .collect()
+}
+
+/// The catalog locale to use for `requested`: itself, or its language
+pub fn resolve_locale(requested: &str) -> Option<String> {
+    let locale = normalize_locale(requested)?;
+    let catalogs = CATALOGS.read().unwrap_or_else(|e| e.into_inner());
+    if catalogs.contains_key(&locale) {
+        return Some(locale);
+    }
+    let language = locale.split('-').next()?;
+    catalogs.contains_key(language).then(|| language.to_string())
+}
+
+/// The instance's default language, else `DEFAULT_LOCALE`, for users who chose none and
+/// requests that name none; English unless one of them has a catalog
+pub fn default_locale() -> String {
+    let instance_locale = INSTANCE_LOCALE.read().unwrap_or_else(|e| e.into_inner()).clone();
+    instance_locale
+        .and_then(|locale| resolve_locale(&locale))
+        .or_else(|| std::env::var("DEFAULT_LOCALE").ok().and_then(|locale| resolve_locale(&locale)))
+        .unwrap_or_else(|| FALLBACK_LOCALE.to_string())
+}
+
+/// Use the default language of the instance settings; None goes back to `DEFAULT_LOCALE`
+pub fn set_instance_locale(locale: Option<String>) {
+    *INSTANCE_LOCALE.write().unwrap_or_else(|e| e.into_inner()) = locale;
+}
+
+/// The text of `key` in `locale` with its placeholders filled in, or the key itself when
+/// no catalog has it
+pub fn translate(locale: &str, key: &str, values: &[(&str, String)]) -> String {
+    match lookup(locale, key) {
+        Some(template) => render_template(&template, values),
+        None => key.to_string(),
+    }
+}
+
+fn lookup(locale: &str, key: &str) -> Option<String> {
+    // Resolved before the lock is taken, which resolving takes as well
+    let default_locale = default_locale();
+    let language = locale.split('-').next().unwrap_or(locale);
+    let catalogs = CATALOGS.read().unwrap_or_else(|e| e.into_inner());
+    let text = [locale, language, default_locale.as_str(), FALLBACK_LOCALE]
+        .into_iter()
+        .find_map(|locale| catalogs.get(locale)?.get(key).cloned());
+    text
+}
+
+/// Replace the `{{name}}` placeholders of a template; unknown ones are left as they are
+pub fn render_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let placeholder = &rest[start..start + end + 2];
+        let name = placeholder[2..placeholder.len() - 2].trim();
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => rendered.push_str(value),
+            None => rendered.push_str(placeholder),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// A notification in `locale`, from the catalog's `<key>.title` and `<key>.message`
+pub fn notification(locale: &str, notification_type: &str, key: &str, values: &[(&str, String)]) -> CreateNotification {
+    CreateNotification {
+        notification_type: notification_type.to_string(),
+        title: translate(locale, &format!("{}.title", key), values),
+        message: translate(locale, &format!("{}.message", key), values),
+        action_url: None,
+        metadata: None,
+    }
+}
+
+/// The locale the user chose, or the default one
+pub async fn locale_of(db: &Database, user_id: Uuid) -> String {
+    chosen_locale(db, user_id).await.unwrap_or_else(default_locale)
+}
+
+/// The locale the user chose, if there is a catalog for it
+async fn chosen_locale(db: &Database, user_id: Uuid) -> Option<String> {
+    let cached = USER_LOCALES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&user_id)
+        .filter(|(read_at, _)| read_at.elapsed() < USER_LOCALE_TTL)
+        .map(|(_, locale)| locale.clone());
+    let chosen = match cached {
+        Some(locale) => locale,
+        None => match db.get_user_locale(user_id).await {
+            Ok(locale) => {
+                let mut cache = USER_LOCALES.lock().unwrap_or_else(|e| e.into_inner());
+                if cache.len() >= MAX_CACHED_USER_LOCALES {
+                    cache.clear();
+                }
+                cache.insert(user_id, (Instant::now(), locale.clone()));
+                locale
+            }
+            Err(e) => {
+                warn!("Failed to get the locale of user {}: {}", user_id, e);
+                None
+            }
+        },
+    };
+
+    chosen.and_then(|locale| resolve_locale(&locale))
+}
+
+/// Forget the cached locale of a user who chose another one
+pub fn forget_user_locale(user_id: Uuid) {
+    USER_LOCALES.lock().unwrap_or_else(|e| e.into_inner()).remove(&user_id);
+}
+
+/// The first locale of an `Accept-Language` header there is a catalog for, by weight
+pub fn negotiate_locale(accept_language: &str) -> Option<String> {
+    let mut ranges: Vec<(&str, f32)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+            let weight = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (tag != "*" && weight > 0.0).then_some((tag, weight))
+        })
+        .collect();
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges.into_iter().find_map(|(tag, _)| resolve_locale(tag))
+}
+
+/// The locale of the running request
+pub fn current_locale() -> String {
+    LOCALE.try_with(|locale| locale.borrow().clone()).unwrap_or_else(|_| default_locale())
+}
+
+/// Write the rest of the running request in the locale `user` chose, if any. Called
+/// once the request is authenticated, so revoked and forged tokens never choose it.
+pub async fn use_user_locale(db: &Database, user_id: Uuid) {
+    if LOCALE.try_with(|_| ()).is_err() {
+        return;
+    }
+    if let Some(chosen) = chosen_locale(db, user_id).await {
+        let _ = LOCALE.try_with(|locale| *locale.borrow_mut() = chosen);
+    }
+}
+
+/// The message of an error code in the locale of the running request, or `fallback`
+/// when no catalog has one. Only messages without values are in the catalogs.
+pub fn error_message(code: &str, fallback: String) -> String {
+    lookup(&current_locale(), &format!("error.{}", code)).unwrap_or(fallback)
+}
+
+/// Runs each request in the locale of its `Accept-Language` header until
+/// [`use_user_locale`] picks the user's
+pub async fn locale_middleware(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(negotiate_locale)
+        .unwrap_or_else(default_locale);
+    LOCALE.scope(RefCell::new(locale), next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let values = [("name", "Ada".to_string()), ("count", "3".to_string())];
+        assert_eq!(render_template("Hi {{name}}, {{ count }} new", &values), "Hi Ada, 3 new");
+        assert_eq!(render_template("{{unknown}} and {{name", &values), "{{unknown}} and {{name");
+    }
+
+    #[test]
+    fn test_normalize_locale() {
+        assert_eq!(normalize_locale("de").as_deref(), Some("de"));
+        assert_eq!(normalize_locale("pt_br").as_deref(), Some("pt-BR"));
+        assert_eq!(normalize_locale("es-419").as_deref(), Some("es-419"));
+        assert_eq!(normalize_locale("../en"), None);
+        assert_eq!(normalize_locale("en-US-x"), None);
+        assert_eq!(normalize_locale(""), None);
+    }
+
+    #[test]
+    fn test_built_in_catalogs_are_complete() {
+        let english = parse_catalog(BUILT_IN_CATALOGS[0].1).unwrap();
+        for (locale, json) in BUILT_IN_CATALOGS {
+            let catalog = parse_catalog(json).unwrap();
+            let missing: Vec<_> = english.keys().filter(|key| !catalog.contains_key(*key)).collect();
+            assert!(missing.is_empty(), "{} lacks {:?}", locale, missing);
+        }
+    }
+
+    #[test]
+    fn test_translate_falls_back_to_the_language_and_english() {
+        let values = [("filename", "lease.pdf".to_string())];
+        assert_eq!(translate("de-AT", "notification.reminder.title", &values), "Erinnerung: lease.pdf");
+        assert_eq!(translate("en", "notification.reminder.title", &values), "Reminder: lease.pdf");
+        assert_eq!(translate("de", "no.such.key", &values), "no.such.key");
+        assert_eq!(resolve_locale("de_ch").as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn test_negotiate_locale_by_weight() {
+        assert_eq!(negotiate_locale("fr-CH, fr;q=0.9, de;q=0.8, en;q=0.7").as_deref(), Some("de"));
+        assert_eq!(negotiate_locale("en;q=0.5, de").as_deref(), Some("de"));
+        assert_eq!(negotiate_locale("fr, *;q=0.5"), None);
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::{anyhow, Result};
+use async_imap::Session;
+use async_native_tls::TlsStream;
+use futures::TryStreamExt;
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+use crate::models::{ImapLabelRule, ImapProcessedAction, ImapSourceConfig};
+
+/// Messages fetched per sync; the rest are picked up by the next poll
+const MAX_MESSAGES_PER_SYNC: usize = 50;
+const CONNECT_TIMEOUT_SECONDS: u64 = 30;
+
+/// Client for an IMAP mailbox source: finds messages matching the source's
+/// filters and flags or moves them once they are processed
+pub struct ImapService {
+    config: ImapSourceConfig,
+}
+
+/// A logged-in session with the source's mailbox selected
+pub struct ImapMailbox {
+    session: Session<TlsStream<TcpStream>>,
+    config: ImapSourceConfig,
+}
+
+impl ImapService {
+    pub fn new(config: ImapSourceConfig) -> Result<Self> {
+        if config.server.trim().is_empty() {
+            return Err(anyhow!("IMAP server cannot be empty"));
+        }
+        if config.username.trim().is_empty() {
+            return Err(anyhow!("IMAP username cannot be empty"));
+        }
+        if config.processed_action == ImapProcessedAction::Move
+            && config.processed_mailbox.as_deref().map(str::trim).unwrap_or("").is_empty()
+        {
+            return Err(anyhow!("IMAP processed_mailbox is required when processed messages are moved"));
+        }
+        Ok(Self { config })
+    }
+
+    pub async fn connect(&self) -> Result<ImapMailbox> {
+        let address = (self.config.server.as_str(), self.config.port);
+        let tcp = tokio::time::timeout(
+            std::time::Duration::from_secs(CONNECT_TIMEOUT_SECONDS),
+            TcpStream::connect(address),
+        )
+        .await
+        .map_err(|_| anyhow!("Timed out connecting to {}:{}", self.config.server, self.config.port))??;
+
+        let tls = async_native_tls::TlsConnector::new()
+            .connect(&self.config.server, tcp)
+            .await
+            .map_err(|e| anyhow!("TLS handshake with {} failed: {}", self.config.server, e))?;
+
+        let client = async_imap::Client::new(tls);
+        let mut session = client
+            .login(&self.config.username, &self.config.password)
+            .await
+            .map_err(|(e, _)| anyhow!("IMAP login failed: {}", e))?;
+
+        session
+            .select(&self.config.mailbox)
+            .await
+            .map_err(|e| anyhow!("Cannot open mailbox '{}': {}", self.config.mailbox, e))?;
+
+        Ok(ImapMailbox { session, config: self.config.clone() })
+    }
+
+    pub async fn test_connection(&self) -> Result<String> {
+        let mut mailbox = self.connect().await?;
+        let matching = mailbox.search().await?.len();
+        mailbox.logout().await;
+        Ok(format!(
+            "Connected to {} - {} matching message(s) in '{}'",
+            self.config.server, matching, self.config.mailbox
+        ))
+    }
+}
+
+impl ImapMailbox {
+    /// UIDs of unprocessed messages matching the source's filters, oldest first
+    pub async fn search(&mut self) -> Result<Vec<u32>> {
+        let mut uids: Vec<u32> = self
+            .session
+            .uid_search(search_query(&self.config))
+            .await?
+            .into_iter()
+            .collect();
+        uids.sort_unstable();
+        Ok(uids)
+    }
+
+    /// Raw RFC 822 source of up to `MAX_MESSAGES_PER_SYNC` matching messages.
+    /// Fetching does not set the seen flag.
+    pub async fn fetch_matching(&mut self) -> Result<Vec<(u32, Vec<u8>)>> {
+        let uids = self.search().await?;
+        if uids.len() > MAX_MESSAGES_PER_SYNC {
+            info!(
+                "{} messages match in '{}', processing the oldest {}",
+                uids.len(),
+                self.config.mailbox,
+                MAX_MESSAGES_PER_SYNC
+            );
+        }
+        let uids: Vec<u32> = uids.into_iter().take(MAX_MESSAGES_PER_SYNC).collect();
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let fetches: Vec<_> = self
+            .session
+            .uid_fetch(uid_set(&uids), "(UID BODY.PEEK[])")
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(fetches
+            .iter()
+            .filter_map(|fetch| Some((fetch.uid?, fetch.body()?.to_vec())))
+            .collect())
+    }
+
+    /// Flag or move processed messages, depending on the source's `processed_action`
+    pub async fn mark_processed(&mut self, uids: &[u32]) -> Result<()> {
+        if uids.is_empty() {
+            return Ok(());
+        }
+        let set = uid_set(uids);
+
+        match self.config.processed_action {
+            ImapProcessedAction::MarkSeen => {
+                let _: Vec<_> = self.session.uid_store(&set, "+FLAGS (\\Seen)").await?.try_collect().await?;
+            }
+            ImapProcessedAction::Move => {
+                let target = self.config.processed_mailbox.clone().unwrap_or_default();
+                if let Err(e) = self.session.uid_mv(&set, &target).await {
+                    // Servers without the MOVE extension
+                    debug!("UID MOVE failed ({}), falling back to copy and delete", e);
+                    self.session.uid_copy(&set, &target).await?;
+                    let _: Vec<_> = self.session.uid_store(&set, "+FLAGS (\\Seen \\Deleted)").await?.try_collect().await?;
+                    let _: Vec<_> = self.session.expunge().await?.try_collect().await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn logout(mut self) {
+        if let Err(e) = self.session.logout().await {
+            warn!("IMAP logout failed: {}", e);
+        }
+    }
+}
+
+/// IMAP SEARCH criteria for unprocessed messages matching the source's filters
+pub fn search_query(config: &ImapSourceConfig) -> String {
+    let mut criteria = vec![match config.processed_action {
+        ImapProcessedAction::MarkSeen => "UNSEEN".to_string(),
+        // Processed messages leave the mailbox, so everything left is pending
+        ImapProcessedAction::Move => "UNDELETED".to_string(),
+    }];
+
+    let senders: Vec<String> = config
+        .from_filters
+        .iter()
+        .map(|f| f.trim())
+        .filter(|f| !f.is_empty())
+        .map(|f| format!("FROM {}", quote(f)))
+        .collect();
+    // OR takes two keys, so n alternatives nest as OR a (OR b c)
+    if let Some(last) = senders.last() {
+        let alternatives = senders[..senders.len() - 1]
+            .iter()
+            .rev()
+            .fold(last.clone(), |rest, sender| format!("OR {} ({})", sender, rest));
+        criteria.push(alternatives);
+    }
+
+    if let Some(subject) = config.subject_filter.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        criteria.push(format!("SUBJECT {}", quote(subject)));
+    }
+
+    criteria.join(" ")
+}
+
+/// Labels of every rule the message matches
+pub fn matching_labels(rules: &[ImapLabelRule], from: Option<&str>, subject: Option<&str>) -> Vec<String> {
+    let from = from.unwrap_or("").to_lowercase();
+    let subject = subject.unwrap_or("").to_lowercase();
+    let mut labels: Vec<String> = Vec::new();
+
+    for rule in rules {
+        let from_matches = rule
+            .from_contains
+            .as_deref()
+            .is_none_or(|needle| from.contains(&needle.to_lowercase()));
+        let subject_matches = rule
+            .subject_contains
+            .as_deref()
+            .is_none_or(|needle| subject.contains(&needle.to_lowercase()));
+        let label = rule.label.trim();
+
+        if from_matches && subject_matches && !label.is_empty() && !labels.iter().any(|l| l == label) {
+            labels.push(label.to_string());
+        }
+    }
+
+    labels
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn uid_set(uids: &[u32]) -> String {
+    uids.iter().map(u32::to_string).collect::<Vec<_>>().join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ImapSourceConfig {
+        ImapSourceConfig {
+            server: "imap.example.com".to_string(),
+            port: 993,
+            username: "scans@example.com".to_string(),
+            password: "secret".to_string(),
+            mailbox: "INBOX".to_string(),
+            from_filters: Vec::new(),
+            subject_filter: None,
+            file_extensions: Vec::new(),
+            label_rules: Vec::new(),
+            processed_action: ImapProcessedAction::MarkSeen,
+            processed_mailbox: None,
+            auto_sync: true,
+            sync_interval_minutes: 5,
+        }
+    }
+
+    #[test]
+    fn test_search_query() {
+        let mut config = config();
+        assert_eq!(search_query(&config), "UNSEEN");
+
+        config.from_filters = vec!["scanner@office".to_string(), "copier".to_string(), "fax \"3\"".to_string()];
+        config.subject_filter = Some("Scan".to_string());
+        assert_eq!(
+            search_query(&config),
+            r#"UNSEEN OR FROM "scanner@office" (OR FROM "copier" (FROM "fax \"3\"")) SUBJECT "Scan""#
+        );
+
+        config.processed_action = ImapProcessedAction::Move;
+        config.from_filters = vec!["copier".to_string()];
+        config.subject_filter = None;
+        assert_eq!(search_query(&config), r#"UNDELETED FROM "copier""#);
+    }
+
+    #[test]
+    fn test_matching_labels() {
+        let rules = vec![
+            ImapLabelRule { from_contains: Some("@acme.com".to_string()), subject_contains: None, label: "ACME".to_string() },
+            ImapLabelRule { from_contains: None, subject_contains: Some("invoice".to_string()), label: "Invoices".to_string() },
+            ImapLabelRule {
+                from_contains: Some("@acme.com".to_string()),
+                subject_contains: Some("contract".to_string()),
+                label: "Contracts".to_string(),
+            },
+        ];
+
+        let labels = matching_labels(&rules, Some("Billing <billing@ACME.com>"), Some("Your Invoice 42"));
+        assert_eq!(labels, vec!["ACME".to_string(), "Invoices".to_string()]);
+        assert!(matching_labels(&rules, None, Some("Hello")).is_empty());
+    }
+}
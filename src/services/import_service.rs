@@ -0,0 +1,386 @@
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+use uuid::Uuid;
+use zip::ZipArchive;
+
+use crate::{
+    models::{
+        CreateUser, EventType, ExportError, ExportManifest, ExportedDocument, Import, ImportStatus, User, UserRole,
+        EXPORT_FORMAT_VERSION, EXPORT_MANIFEST_NAME,
+    },
+    services::event_service::EventService,
+    utils::security::generate_secure_password,
+    AppState,
+};
+
+/// Directory under the upload path uploaded archives are kept in until they are imported
+const IMPORTS_DIR: &str = "imports";
+/// Documents restored between progress updates
+const PROGRESS_BATCH_SIZE: usize = 25;
+/// Failures kept with an import; later ones are only counted
+const MAX_RECORDED_ERRORS: usize = 100;
+/// Largest manifest read from an archive
+const MAX_MANIFEST_BYTES: u64 = 1024 * 1024 * 1024;
+/// Restored documents without OCR results queue behind regular uploads
+const IMPORT_OCR_PRIORITY: i32 = 3;
+
+type SharedArchive = Arc<Mutex<ZipArchive<File>>>;
+
+/// Restores export archives. Users and labels of the archive are matched to existing
+/// ones by name, or created when the import asks for it; documents keep their IDs
+/// unless another document has them. Every file is checked against the SHA-256 in the
+/// manifest before it is stored. Documents whose owner already has the same file are
+/// skipped, so an import interrupted by a restart starts over without duplicates.
+pub struct ImportService {
+    state: Arc<AppState>,
+}
+
+/// Where the users and labels of the archive end up on this server
+struct IdMap {
+    users: HashMap<Uuid, Uuid>,
+    labels: HashMap<Uuid, Uuid>,
+}
+
+impl ImportService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Where the uploaded archive of an import is kept until it is imported
+    pub fn archive_path(&self, import_id: Uuid) -> PathBuf {
+        self.state
+            .file_service
+            .get_subdirectory_path(IMPORTS_DIR)
+            .join(format!("{}.zip", import_id))
+    }
+
+    /// Run the imports again that were queued or running when the server stopped
+    pub async fn resume_interrupted(&self) -> Result<()> {
+        for import in self.state.db.get_unfinished_imports().await? {
+            info!("Restarting import {}", import.id);
+            if let Err(e) = self.run(import.id).await {
+                warn!("Import {} stopped: {}", import.id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Restore the import's archive and remove it once done
+    pub async fn run(&self, import_id: Uuid) -> Result<Import> {
+        let import = self
+            .state
+            .db
+            .get_import(import_id)
+            .await?
+            .ok_or_else(|| anyhow!("Import {} not found", import_id))?;
+        if matches!(import.status, ImportStatus::Completed | ImportStatus::Failed) {
+            return Ok(import);
+        }
+
+        let result = self.restore(&import).await;
+        let archive_path = self.archive_path(import.id);
+        if let Err(e) = tokio::fs::remove_file(&archive_path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove imported archive {}: {}", archive_path.display(), e);
+            }
+        }
+
+        match result {
+            Ok(()) => {
+                info!("Import {} completed", import.id);
+                self.state.db.finish_import(import.id, ImportStatus::Completed, None).await
+            }
+            Err(e) => {
+                warn!("Import {} failed: {}", import.id, e);
+                self.state
+                    .db
+                    .finish_import(import.id, ImportStatus::Failed, Some(&e.to_string()))
+                    .await
+            }
+        }
+    }
+
+    async fn restore(&self, import: &Import) -> Result<()> {
+        let user = self
+            .state
+            .db
+            .get_user_by_id(import.user_id)
+            .await?
+            .ok_or_else(|| anyhow!("User {} no longer exists", import.user_id))?;
+        if import.recreate_users && user.role != UserRole::Admin {
+            return Err(anyhow!("Only admins can recreate users"));
+        }
+
+        let archive_path = self.archive_path(import.id);
+        let archive = tokio::task::spawn_blocking(move || -> Result<ZipArchive<File>> {
+            Ok(ZipArchive::new(File::open(archive_path)?)?)
+        })
+        .await??;
+        let archive: SharedArchive = Arc::new(Mutex::new(archive));
+
+        let manifest = read_entry(archive.clone(), EXPORT_MANIFEST_NAME.to_string(), MAX_MANIFEST_BYTES)
+            .await
+            .map_err(|e| anyhow!("Not an export archive: {}", e))?;
+        let manifest: ExportManifest = serde_json::from_slice(&manifest)?;
+        if manifest.format_version > EXPORT_FORMAT_VERSION {
+            return Err(anyhow!(
+                "The archive was written by Readur {} in manifest format {}, newer than this server supports",
+                manifest.readur_version,
+                manifest.format_version
+            ));
+        }
+
+        self.state
+            .db
+            .start_import(import.id, manifest.documents.len() as i32)
+            .await?;
+        let (ids, created_users, created_labels) = self.map_users_and_labels(import, &user, &manifest).await?;
+        self.state
+            .db
+            .set_import_created_counts(import.id, created_users, created_labels)
+            .await?;
+
+        let mut recorded_errors = 0;
+        for batch in manifest.documents.chunks(PROGRESS_BATCH_SIZE) {
+            let (mut imported, mut skipped, mut failed) = (0, 0, 0);
+            let mut errors = Vec::new();
+
+            for document in batch {
+                let owner = ids.users.get(&document.user_id).copied().unwrap_or(user.id);
+                match self.restore_document(&archive, document, owner, &ids).await {
+                    Ok(true) => imported += 1,
+                    Ok(false) => skipped += 1,
+                    Err(e) => {
+                        failed += 1;
+                        if recorded_errors < MAX_RECORDED_ERRORS {
+                            recorded_errors += 1;
+                            errors.push(ExportError { document_id: document.id, error: e.to_string() });
+                        }
+                    }
+                }
+            }
+
+            self.state
+                .db
+                .update_import_progress(import.id, imported, skipped, failed, &errors)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Match the archive's users and labels to this server's, creating those the import
+    /// asks for. Returns the mapping and how many users and labels were created.
+    async fn map_users_and_labels(&self, import: &Import, user: &User, manifest: &ExportManifest) -> Result<(IdMap, i32, i32)> {
+        let db = &self.state.db;
+        let mut ids = IdMap { users: HashMap::new(), labels: HashMap::new() };
+        let mut created_users = 0;
+        let mut created_labels = 0;
+
+        for exported in &manifest.users {
+            let user_id = if !import.recreate_users {
+                user.id
+            } else if let Some(existing) = db.get_user_by_username(&exported.username).await? {
+                existing.id
+            } else if let Some(existing) = db.get_user_by_email(&exported.email).await? {
+                existing.id
+            } else {
+                // The archive has no passwords; an admin sets new ones
+                let created = db
+                    .create_user(CreateUser {
+                        username: exported.username.clone(),
+                        email: exported.email.clone(),
+                        password: generate_secure_password(32),
+                        role: Some(UserRole::try_from(exported.role.clone()).unwrap_or(UserRole::User)),
+                    })
+                    .await?;
+                info!("Import {} created user {} ({})", import.id, created.username, created.id);
+                created_users += 1;
+                created.id
+            };
+            ids.users.insert(exported.id, user_id);
+        }
+
+        for label in &manifest.labels {
+            let owner = match label.user_id {
+                None => None,
+                Some(user_id) => Some(ids.users.get(&user_id).copied().unwrap_or(user.id)),
+            };
+            let label_id = match db.find_label_id_by_name(owner, &label.name).await? {
+                Some(label_id) => label_id,
+                // Only admins may add system labels
+                None if import.recreate_labels && (owner.is_some() || user.role == UserRole::Admin) => {
+                    let parent_id = label.parent_id.and_then(|parent_id| ids.labels.get(&parent_id).copied());
+                    created_labels += 1;
+                    db.create_imported_label(label, owner, parent_id).await?
+                }
+                None => continue,
+            };
+            ids.labels.insert(label.id, label_id);
+        }
+
+        Ok((ids, created_users, created_labels))
+    }
+
+    /// Returns false when the owner already has the document's file
+    async fn restore_document(
+        &self,
+        archive: &SharedArchive,
+        document: &ExportedDocument,
+        owner: Uuid,
+        ids: &IdMap,
+    ) -> Result<bool> {
+        let db = &self.state.db;
+        let file = document
+            .file
+            .clone()
+            .ok_or_else(|| anyhow!("The archive has no file for this document"))?;
+        let data = read_entry(archive.clone(), file, document.file_size.max(0) as u64).await?;
+        let file_hash = format!("{:x}", Sha256::digest(&data));
+        if document.file_hash.as_deref().is_some_and(|expected| !expected.eq_ignore_ascii_case(&file_hash)) {
+            return Err(anyhow!("The file does not match its hash in the manifest"));
+        }
+        if db.get_document_by_user_and_hash(owner, &file_hash).await?.is_some() {
+            return Ok(false);
+        }
+
+        let document_id = if db.document_id_exists(document.id).await? { Uuid::new_v4() } else { document.id };
+        let file_service = &self.state.file_service;
+        let file_path = file_service
+            .store_document_content(owner, document_id, &document.filename, &data, &file_hash)
+            .await?;
+        let mut restored = file_service.create_document_with_id(
+            document_id,
+            &document.filename,
+            &document.original_filename,
+            &file_path,
+            data.len() as i64,
+            &document.mime_type,
+            owner,
+            Some(file_hash),
+            document.original_created_at,
+            document.original_modified_at,
+            document.source_path.clone(),
+            document.source_type.clone(),
+            None,
+            None,
+            None,
+            None,
+            document.source_metadata.clone(),
+        );
+        restored.content = document.content.clone();
+        restored.tags = document.tags.clone();
+        restored.created_at = document.created_at;
+        restored.updated_at = document.updated_at;
+        let has_ocr = document.ocr_status.as_deref() == Some("completed");
+        if has_ocr {
+            restored.ocr_text = document.ocr_text.clone();
+            restored.ocr_confidence = document.ocr_confidence;
+            restored.ocr_word_count = document.ocr_word_count;
+            restored.ocr_status = document.ocr_status.clone();
+            restored.ocr_completed_at = document.ocr_completed_at;
+        }
+        let restored = match db.create_document(restored).await {
+            Ok(restored) => restored,
+            Err(e) => {
+                // The content is not kept for this document after all
+                if let Err(release_err) = file_service.release_blob(&file_path).await {
+                    warn!("Failed to release blob {}: {}", file_path, release_err);
+                }
+                return Err(e);
+            }
+        };
+
+        for label_id in document.label_ids.iter().filter_map(|label_id| ids.labels.get(label_id)) {
+            db.assign_label(restored.id, *label_id, owner).await?;
+        }
+        if let Some(custom_fields) = &document.custom_fields {
+            match db
+                .find_document_type_id(owner, custom_fields.document_type_id, &custom_fields.document_type_name)
+                .await?
+            {
+                Some(document_type_id) => {
+                    db.set_document_custom_fields(restored.id, document_type_id, &custom_fields.fields.0)
+                        .await?
+                }
+                None => warn!(
+                    "Document type '{}' of document {} does not exist, its custom fields are not restored",
+                    custom_fields.document_type_name, document.id
+                ),
+            }
+        }
+        db.restore_document_graph(restored.id, &document.graph).await?;
+
+        if !has_ocr {
+            if let Err(e) = self
+                .state
+                .queue_service
+                .enqueue_document(restored.id, IMPORT_OCR_PRIORITY, restored.file_size)
+                .await
+            {
+                warn!("Failed to enqueue imported document {} for OCR: {}", restored.id, e);
+            }
+        }
+        EventService::new(db.clone())
+            .publish_best_effort(
+                EventType::DocumentCreated,
+                Some(restored.user_id),
+                Some(restored.id),
+                serde_json::json!({
+                    "document_id": restored.id,
+                    "filename": restored.filename,
+                    "mime_type": restored.mime_type,
+                    "file_size": restored.file_size,
+                    "source_type": restored.source_type,
+                }),
+            )
+            .await;
+
+        Ok(true)
+    }
+}
+
+/// Read an archive entry off the async runtime, failing when it is larger than `max_size`
+async fn read_entry(archive: SharedArchive, name: String, max_size: u64) -> Result<Vec<u8>> {
+    tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+        let mut archive = archive.lock().map_err(|_| anyhow!("Archive lock poisoned"))?;
+        let entry = archive.by_name(&name)?;
+        let mut data = Vec::new();
+        entry.take(max_size.saturating_add(1)).read_to_end(&mut data)?;
+        if data.len() as u64 > max_size {
+            return Err(anyhow!("{} is larger than {} bytes", name, max_size));
+        }
+        Ok(data)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn archive_with(name: &str, data: &[u8]) -> SharedArchive {
+        let mut writer = zip::ZipWriter::new(tempfile::tempfile().unwrap());
+        writer.start_file(name, zip::write::FileOptions::default()).unwrap();
+        writer.write_all(data).unwrap();
+        let file = writer.finish().unwrap();
+        Arc::new(Mutex::new(ZipArchive::new(file).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_read_entry_enforces_size() {
+        let archive = archive_with("files/a/invoice.pdf", b"%PDF-1.7");
+        let data = read_entry(archive.clone(), "files/a/invoice.pdf".to_string(), 8).await.unwrap();
+        assert_eq!(data, b"%PDF-1.7");
+
+        assert!(read_entry(archive.clone(), "files/a/invoice.pdf".to_string(), 7).await.is_err());
+        assert!(read_entry(archive, EXPORT_MANIFEST_NAME.to_string(), MAX_MANIFEST_BYTES).await.is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Admin-defined hooks around ingestion, like Paperless's pre- and post-consume
+//! scripts.
+//!
+//! Pre-consume hooks see every file before it is stored, in order, and may replace
+//! or reject it. Commands get the file at `READUR_DOCUMENT_PATH` and may rewrite it
+//! in place; exiting with a non-zero status rejects it. Webhooks get the file as
+//! the request body and answer 204 to keep it, 200 with a new file to replace it,
+//! or 4xx to reject it.
+//!
+//! Post-consume hooks are told about a document after its OCR: commands get its
+//! metadata as JSON on stdin, webhooks as the request body. Their failures are only
+//! recorded on the hook.
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use reqwest::{header::CONTENT_TYPE, Client, StatusCode};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{ContentDestination, Document, HookFailurePolicy, IngestionHook, IngestionHookKind, IngestionHookStage};
+use crate::services::data_residency;
+use crate::services::event_service::{sign_payload, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+/// Directory command hooks are run from; commands are disabled when unset
+static HOOKS_DIR: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    std::env::var("INGESTION_HOOKS_DIR").ok().filter(|dir| !dir.trim().is_empty()).map(PathBuf::from)
+});
+
+pub const HOOK_STAGE_HEADER: &str = "X-Readur-Hook-Stage";
+pub const HOOK_FILENAME_HEADER: &str = "X-Readur-Filename";
+pub const HOOK_USER_HEADER: &str = "X-Readur-User-Id";
+const HOOK_STAGE_ENV: &str = "READUR_HOOK_STAGE";
+
+/// Longest reason kept from a hook's output
+const MAX_REASON_LENGTH: usize = 500;
+
+/// A file refused by a pre-consume hook, or by a hook that failed with the
+/// `reject` policy
+#[derive(Debug, Clone)]
+pub struct IngestionHookRejected {
+    pub filename: String,
+    pub hook: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for IngestionHookRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} was rejected by ingestion hook '{}': {}", self.filename, self.hook, self.reason)
+    }
+}
+
+impl std::error::Error for IngestionHookRejected {}
+
+/// A file about to be ingested, as pre-consume hooks see it
+#[derive(Debug, Clone, Copy)]
+pub struct PreConsumeFile<'a> {
+    pub filename: &'a str,
+    pub mime_type: &'a str,
+    pub user_id: Uuid,
+    pub source_type: Option<&'a str>,
+    pub data: &'a [u8],
+}
+
+/// What a hook that ran made of a file
+#[derive(Debug)]
+enum Verdict {
+    /// Keep the file, or replace it with new contents
+    Accept(Option<Vec<u8>>),
+    Reject(String),
+}
+
+pub struct IngestionHookService {
+    db: Database,
+    client: Client,
+}
+
+impl IngestionHookService {
+    pub fn new(db: Database) -> Self {
+        Self { db, client: Client::new() }
+    }
+
+    pub fn hooks_dir() -> Option<&'static Path> {
+        HOOKS_DIR.as_deref()
+    }
+
+    /// Run the enabled pre-consume hooks over a file in order. Returns the contents
+    /// to ingest instead when a hook replaced them, and `IngestionHookRejected` when
+    /// a hook refused the file.
+    pub async fn run_pre_consume(
+        &self,
+        file: PreConsumeFile<'_>,
+    ) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync>> {
+        let hooks = self.db.get_enabled_ingestion_hooks(IngestionHookStage::PreConsume).await?;
+        let mut replaced: Option<Vec<u8>> = None;
+
+        for hook in &hooks {
+            let current = PreConsumeFile { data: replaced.as_deref().unwrap_or(file.data), ..file };
+            let result = match hook.kind {
+                IngestionHookKind::Command => self.run_pre_consume_command(hook, current).await,
+                IngestionHookKind::Webhook => self.call_pre_consume_webhook(hook, current).await,
+            };
+            self.record_run(hook, result.as_ref().err().map(String::as_str)).await;
+
+            match result {
+                Ok(Verdict::Accept(Some(data))) => {
+                    info!("Ingestion hook '{}' replaced {} ({} bytes)", hook.name, file.filename, data.len());
+                    replaced = Some(data);
+                }
+                Ok(Verdict::Accept(None)) => {}
+                Ok(Verdict::Reject(reason)) => {
+                    return Err(Box::new(IngestionHookRejected {
+                        filename: file.filename.to_string(),
+                        hook: hook.name.clone(),
+                        reason,
+                    }));
+                }
+                Err(e) if hook.on_failure == HookFailurePolicy::Ignore => {
+                    warn!("Ingesting {} without ingestion hook '{}', which failed: {}", file.filename, hook.name, e);
+                }
+                Err(e) => {
+                    warn!("Ingestion hook '{}' failed for {}: {}", hook.name, file.filename, e);
+                    return Err(Box::new(IngestionHookRejected {
+                        filename: file.filename.to_string(),
+                        hook: hook.name.clone(),
+                        reason: format!("the hook failed: {}", e),
+                    }));
+                }
+            }
+        }
+
+        Ok(replaced)
+    }
+
+    /// Tell the enabled post-consume hooks about a document whose OCR is done
+    pub async fn run_post_consume(&self, document: &Document) -> anyhow::Result<()> {
+        let hooks = self.db.get_enabled_ingestion_hooks(IngestionHookStage::PostConsume).await?;
+        if hooks.is_empty() {
+            return Ok(());
+        }
+        let payload = serde_json::to_vec(&post_consume_payload(document))?;
+
+        for hook in &hooks {
+            let result = match hook.kind {
+                IngestionHookKind::Command => self.run_post_consume_command(hook, document, &payload).await,
+                IngestionHookKind::Webhook => self.call_post_consume_webhook(hook, document.id, &payload).await,
+            };
+            if let Err(e) = &result {
+                warn!("Post-consume hook '{}' failed for document {}: {}", hook.name, document.id, e);
+            }
+            self.record_run(hook, result.as_ref().err().map(String::as_str)).await;
+        }
+        Ok(())
+    }
+
+    async fn record_run(&self, hook: &IngestionHook, error: Option<&str>) {
+        if let Err(e) = self.db.record_ingestion_hook_run(hook.id, error).await {
+            warn!("Failed to record run of ingestion hook '{}': {}", hook.name, e);
+        }
+    }
+
+    async fn run_pre_consume_command(&self, hook: &IngestionHook, file: PreConsumeFile<'_>) -> Result<Verdict, String> {
+        let program = command_path(hook)?;
+        let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+        let work_dir = PathBuf::from(temp_dir).join(format!("ingestion_hook_{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&work_dir)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", work_dir.display(), e))?;
+
+        let result = run_pre_consume_command_in(&work_dir, &program, hook, file).await;
+        let _ = tokio::fs::remove_dir_all(&work_dir).await;
+        result
+    }
+
+    async fn call_pre_consume_webhook(&self, hook: &IngestionHook, file: PreConsumeFile<'_>) -> Result<Verdict, String> {
+        data_residency::authorize(self.db.get_pool(), ContentDestination::IngestionHook, &hook.target, &[], file.data.len())
+            .await?;
+        let timestamp = Utc::now().timestamp();
+        let mut request = self
+            .client
+            .post(&hook.target)
+            .timeout(timeout(hook))
+            .header(CONTENT_TYPE, file.mime_type)
+            .header(HOOK_STAGE_HEADER, IngestionHookStage::PreConsume.to_string())
+            .header(HOOK_FILENAME_HEADER, urlencoding::encode(file.filename).into_owned())
+            .header(HOOK_USER_HEADER, file.user_id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        if let Some(secret) = &hook.secret {
+            request = request.header(SIGNATURE_HEADER, sign_payload(secret, timestamp, file.data));
+        }
+        let response = request
+            .body(file.data.to_vec())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let status = response.status();
+        if status.is_client_error() {
+            let body = response.text().await.unwrap_or_default();
+            return Ok(Verdict::Reject(reason_or(&body, &format!("the webhook answered {}", status))));
+        }
+        if !status.is_success() {
+            return Err(format!("The webhook answered {}", status));
+        }
+        if status == StatusCode::NO_CONTENT {
+            return Ok(Verdict::Accept(None));
+        }
+        let body = response.bytes().await.map_err(|e| format!("Failed to read the response: {}", e))?;
+        Ok(Verdict::Accept((!body.is_empty() && body.as_ref() != file.data).then(|| body.to_vec())))
+    }
+
+    async fn run_post_consume_command(&self, hook: &IngestionHook, document: &Document, payload: &[u8]) -> Result<(), String> {
+        let program = command_path(hook)?;
+        let mut child = Command::new(&program)
+            .env(HOOK_STAGE_ENV, IngestionHookStage::PostConsume.to_string())
+            .env("READUR_DOCUMENT_ID", document.id.to_string())
+            .env("READUR_FILENAME", &document.original_filename)
+            .env("READUR_MIME_TYPE", &document.mime_type)
+            .env("READUR_USER_ID", document.user_id.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", program.display(), e))?;
+
+        let run = async {
+            if let Some(mut stdin) = child.stdin.take() {
+                // A command that does not read its input closes the pipe early
+                let _ = stdin.write_all(payload).await;
+            }
+            child.wait_with_output().await
+        };
+        let output = tokio::time::timeout(timeout(hook), run)
+            .await
+            .map_err(|_| format!("Timed out after {} seconds", hook.timeout_seconds))?
+            .map_err(|e| format!("Failed to run {}: {}", program.display(), e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(reason_or(&stderr, &format!("exited with {}", output.status)));
+        }
+        Ok(())
+    }
+
+    async fn call_post_consume_webhook(&self, hook: &IngestionHook, document_id: Uuid, payload: &[u8]) -> Result<(), String> {
+        data_residency::authorize(
+            self.db.get_pool(),
+            ContentDestination::IngestionHook,
+            &hook.target,
+            &[document_id],
+            payload.len(),
+        )
+        .await?;
+        let timestamp = Utc::now().timestamp();
+        let mut request = self
+            .client
+            .post(&hook.target)
+            .timeout(timeout(hook))
+            .header(CONTENT_TYPE, "application/json")
+            .header(HOOK_STAGE_HEADER, IngestionHookStage::PostConsume.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        if let Some(secret) = &hook.secret {
+            request = request.header(SIGNATURE_HEADER, sign_payload(secret, timestamp, payload));
+        }
+        let response = request
+            .body(payload.to_vec())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("The webhook answered {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+async fn run_pre_consume_command_in(
+    work_dir: &Path,
+    program: &Path,
+    hook: &IngestionHook,
+    file: PreConsumeFile<'_>,
+) -> Result<Verdict, String> {
+    let extension = Path::new(file.filename).extension().and_then(|e| e.to_str()).unwrap_or("bin");
+    let document_path = work_dir.join(format!("document.{}", extension));
+    tokio::fs::write(&document_path, file.data)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", document_path.display(), e))?;
+
+    let output = tokio::time::timeout(
+        timeout(hook),
+        Command::new(program)
+            .current_dir(work_dir)
+            .env(HOOK_STAGE_ENV, IngestionHookStage::PreConsume.to_string())
+            .env("READUR_DOCUMENT_PATH", &document_path)
+            .env("READUR_FILENAME", file.filename)
+            .env("READUR_MIME_TYPE", file.mime_type)
+            .env("READUR_USER_ID", file.user_id.to_string())
+            .env("READUR_SOURCE_TYPE", file.source_type.unwrap_or(""))
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| format!("Timed out after {} seconds", hook.timeout_seconds))?
+    .map_err(|e| format!("Failed to run {}: {}", program.display(), e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Ok(Verdict::Reject(reason_or(&stderr, &format!("the command exited with {}", output.status))));
+    }
+
+    let data = tokio::fs::read(&document_path)
+        .await
+        .map_err(|e| format!("The command removed {}: {}", document_path.display(), e))?;
+    if data.is_empty() {
+        return Err("The command left an empty file".to_string());
+    }
+    Ok(Verdict::Accept((data != file.data).then_some(data)))
+}
+
+/// Where a command hook's executable is; never outside the hooks directory
+fn command_path(hook: &IngestionHook) -> Result<PathBuf, String> {
+    let dir = HOOKS_DIR.as_deref().ok_or("INGESTION_HOOKS_DIR is not set, so commands cannot run")?;
+    Ok(dir.join(&hook.target))
+}
+
+fn timeout(hook: &IngestionHook) -> Duration {
+    Duration::from_secs(hook.timeout_seconds.max(1) as u64)
+}
+
+/// A hook's own explanation, shortened, or `fallback` when it gave none
+fn reason_or(output: &str, fallback: &str) -> String {
+    let output = output.trim();
+    if output.is_empty() {
+        return fallback.to_string();
+    }
+    match output.char_indices().nth(MAX_REASON_LENGTH) {
+        Some((end, _)) => format!("{}…", &output[..end]),
+        None => output.to_string(),
+    }
+}
+
+/// What post-consume hooks are told about a document
+fn post_consume_payload(document: &Document) -> serde_json::Value {
+    serde_json::json!({
+        "document_id": document.id,
+        "user_id": document.user_id,
+        "filename": document.filename,
+        "original_filename": document.original_filename,
+        "mime_type": document.mime_type,
+        "file_size": document.file_size,
+        "file_hash": document.file_hash,
+        "tags": document.tags,
+        "source_type": document.source_type,
+        "source_id": document.source_id,
+        "source_path": document.source_path,
+        "ocr_confidence": document.ocr_confidence,
+        "ocr_word_count": document.ocr_word_count,
+        "created_at": document.created_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reason_or() {
+        assert_eq!(reason_or("  \n", "exited with 1"), "exited with 1");
+        assert_eq!(reason_or(" not an invoice\n", "exited with 1"), "not an invoice");
+        let long = "x".repeat(MAX_REASON_LENGTH + 10);
+        assert_eq!(reason_or(&long, "").chars().count(), MAX_REASON_LENGTH + 1);
+    }
+}
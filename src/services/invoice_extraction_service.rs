@@ -0,0 +1,243 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{
+    invoice_field_definitions, Document, DocumentCustomFields, InvoiceData, InvoiceExtractionResponse, InvoiceLineItem,
+    INVOICE_DOCUMENT_TYPE, INVOICE_EXTRACTION_MODES,
+};
+use crate::ocr::invoice_fields::{self, parse_amount, parse_date};
+use crate::services::llm::llm_service::{LLMService, PromptTemplate};
+
+/// Whether to extract invoice data after OCR from documents that look like invoices
+static ENABLED_AFTER_OCR: Lazy<bool> = Lazy::new(|| {
+    std::env::var("INVOICE_EXTRACTION_ENABLED")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+});
+
+/// Extraction mode after OCR, see `INVOICE_EXTRACTION_MODES`
+static MODE_AFTER_OCR: Lazy<&'static str> = Lazy::new(|| {
+    let mode = std::env::var("INVOICE_EXTRACTION_MODE").unwrap_or_default();
+    INVOICE_EXTRACTION_MODES
+        .into_iter()
+        .find(|known| known.eq_ignore_ascii_case(mode.trim()))
+        .unwrap_or("auto")
+});
+
+/// Characters of a document's text shown to the LLM
+const LLM_TEXT_CHARS: usize = 8000;
+
+const LLM_SYSTEM_PROMPT: &str = "You extract data from invoices. Answer with JSON only, in this form: \
+    {\"vendor\": \"<who issued the invoice>\", \"invoice_number\": \"<number>\", \
+    \"invoice_date\": \"YYYY-MM-DD\", \"due_date\": \"YYYY-MM-DD\", \"total\": <gross total>, \
+    \"tax\": <tax amount>, \"currency\": \"<ISO 4217 code>\", \
+    \"line_items\": [{\"description\": \"<text>\", \"quantity\": <number>, \"unit_price\": <number>, \"amount\": <number>}]}. \
+    Use null for anything the text does not show and plain numbers without currency symbols for amounts.";
+const LLM_PROMPT: PromptTemplate = PromptTemplate::new("invoice", 1);
+
+/// Pulls vendor, number, dates, amounts and line items out of invoices, with the LLM
+/// or with label rules, and stores them as custom fields of the `Invoice` document type
+pub struct InvoiceExtractionService {
+    db: Database,
+    llm_service: LLMService,
+}
+
+impl InvoiceExtractionService {
+    pub fn new(db: Database) -> Self {
+        let llm_service = LLMService::new(db.get_pool().clone());
+        Self { db, llm_service }
+    }
+
+    /// Whether invoice extraction should run automatically after OCR
+    pub fn enabled_after_ocr() -> bool {
+        *ENABLED_AFTER_OCR
+    }
+
+    /// Whether the `llm` mode can be used
+    pub fn llm_available(&self) -> bool {
+        self.llm_service.chat_enabled()
+    }
+
+    /// Extract invoice data from the document's text and merge it into its `Invoice`
+    /// custom fields, replacing values of fields that were found. A document of
+    /// another type becomes an `Invoice`.
+    pub async fn extract(&self, document: &Document, mode: &str) -> Result<InvoiceExtractionResponse> {
+        let text = document_text(document)
+            .ok_or_else(|| anyhow!("Document {} has no text to extract invoice data from", document.id))?;
+
+        let (extracted_by, mut invoice) = match mode {
+            "rules" => ("rules", invoice_fields::extract_invoice(text)),
+            "llm" => ("llm", self.extract_with_llm(text).await?),
+            _ if self.llm_available() => match self.extract_with_llm(text).await {
+                Ok(invoice) => ("llm", invoice),
+                Err(e) => {
+                    warn!("LLM invoice extraction failed for document {}, using rules: {}", document.id, e);
+                    ("rules", invoice_fields::extract_invoice(text))
+                }
+            },
+            _ => ("rules", invoice_fields::extract_invoice(text)),
+        };
+
+        // The correspondent names the vendor better than the first line of a letterhead
+        if extracted_by == "rules" {
+            if let Some(correspondent) = self.db.get_document_correspondent(document.id).await? {
+                invoice.vendor = Some(correspondent.name);
+            }
+        }
+
+        let custom_fields = self.store(document.id, document.user_id, &invoice).await?;
+        info!(
+            "Extracted invoice data of document {} with {} ({} fields)",
+            document.id,
+            extracted_by,
+            custom_fields.fields.len()
+        );
+
+        Ok(InvoiceExtractionResponse {
+            extracted_by: extracted_by.to_string(),
+            invoice,
+            custom_fields,
+        })
+    }
+
+    /// Extract invoice data from a freshly processed document when it looks like an
+    /// invoice and has no type yet
+    pub async fn extract_after_ocr(&self, document: &Document) -> Result<()> {
+        if !document_text(document).is_some_and(invoice_fields::looks_like_invoice) {
+            return Ok(());
+        }
+        if self.db.get_document_custom_fields(document.id).await?.is_some() {
+            return Ok(());
+        }
+        self.extract(document, *MODE_AFTER_OCR).await?;
+        Ok(())
+    }
+
+    async fn extract_with_llm(&self, text: &str) -> Result<InvoiceData> {
+        let excerpt: String = text.chars().take(LLM_TEXT_CHARS).collect();
+        let answer = self
+            .llm_service
+            .complete(LLM_PROMPT, LLM_SYSTEM_PROMPT, &excerpt)
+            .await
+            .map_err(|e| anyhow!(e))?;
+
+        parse_llm_answer(&answer).ok_or_else(|| anyhow!("LLM returned no invoice data: {}", answer))
+    }
+
+    async fn store(
+        &self,
+        document_id: Uuid,
+        user_id: Uuid,
+        invoice: &InvoiceData,
+    ) -> Result<DocumentCustomFields> {
+        let document_type = self
+            .db
+            .ensure_document_type(user_id, INVOICE_DOCUMENT_TYPE, &invoice_field_definitions())
+            .await?;
+
+        let mut values: Map<String, Value> = match self.db.get_document_custom_fields(document_id).await? {
+            Some(existing) if existing.document_type_id == document_type.id => existing.fields.0,
+            _ => Map::new(),
+        };
+        values.extend(invoice.to_custom_fields());
+
+        let values = document_type.validate_values(&values).map_err(|e| anyhow!(e))?;
+        self.db.set_document_custom_fields(document_id, document_type.id, &values).await?;
+        self.db
+            .get_document_custom_fields(document_id)
+            .await?
+            .ok_or_else(|| anyhow!("Custom fields of document {} disappeared", document_id))
+    }
+}
+
+/// OCR text, or the content of documents that have no OCR text
+fn document_text(document: &Document) -> Option<&str> {
+    let usable = |text: &&str| !text.trim().is_empty();
+    document
+        .ocr_text
+        .as_deref()
+        .filter(usable)
+        .or_else(|| document.content.as_deref().filter(usable))
+}
+
+/// Invoice data from the LLM's JSON answer. Amounts and dates given as text are parsed
+/// like printed ones.
+fn parse_llm_answer(answer: &str) -> Option<InvoiceData> {
+    let answer: Value = serde_json::from_str(answer).ok()?;
+    let answer = answer.as_object()?;
+
+    let text = |key: &str| {
+        answer
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let date = |key: &str| text(key).and_then(|value| parse_date(&value));
+
+    let line_items = answer
+        .get("line_items")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let description = item.get("description")?.as_str()?.trim();
+                    (!description.is_empty()).then(|| InvoiceLineItem {
+                        description: description.to_string(),
+                        quantity: amount(item.get("quantity")),
+                        unit_price: amount(item.get("unit_price")),
+                        amount: amount(item.get("amount")),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(InvoiceData {
+        vendor: text("vendor"),
+        invoice_number: text("invoice_number"),
+        invoice_date: date("invoice_date"),
+        due_date: date("due_date"),
+        total: amount(answer.get("total")),
+        tax: amount(answer.get("tax")),
+        currency: text("currency")
+            .map(|code| code.to_ascii_uppercase())
+            .filter(|code| code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())),
+        line_items,
+    })
+}
+
+fn amount(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => parse_amount(text).map(|(amount, _)| amount),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_llm_answer() {
+        let answer = r#"{"vendor": "ACME Corp", "invoice_number": "INV-7", "invoice_date": "2024-05-15",
+            "due_date": null, "total": "1.154,30", "tax": 184.3, "currency": "eur",
+            "line_items": [{"description": "Consulting", "quantity": 10, "unit_price": 85, "amount": 850}]}"#;
+
+        let invoice = parse_llm_answer(answer).unwrap();
+        assert_eq!(invoice.vendor.as_deref(), Some("ACME Corp"));
+        assert_eq!(invoice.invoice_date, chrono::NaiveDate::from_ymd_opt(2024, 5, 15));
+        assert_eq!(invoice.due_date, None);
+        assert_eq!(invoice.total, Some(1154.3));
+        assert_eq!(invoice.currency.as_deref(), Some("EUR"));
+        assert_eq!(invoice.line_items[0].amount, Some(850.0));
+        assert_eq!(parse_llm_answer("no invoice here"), None);
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{Document, LabelRule, LabelRuleSubject};
+use crate::services::event_service::EventService;
+
+/// Documents loaded per batch when applying rules to existing documents
+const BACKFILL_BATCH_SIZE: i64 = 100;
+
+/// Applies labels by their rules: rules on the source and MIME type when a document is
+/// ingested, all rules once OCR has produced its text, and all rules to existing
+/// documents on request
+pub struct LabelRuleService {
+    db: Database,
+}
+
+impl LabelRuleService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Label a freshly ingested document by the owner's rules that do not need its
+    /// text. Returns the number of labels added.
+    pub async fn apply_at_ingestion(&self, document: &Document) -> Result<usize> {
+        let mut rules = self.db.get_enabled_label_rules(Some(document.user_id)).await?;
+        rules.retain(|rule| !rule.needs_text());
+        self.apply(document, None, &rules, &mut HashMap::new()).await
+    }
+
+    /// Label a freshly processed document by the owner's rules. Returns the number of
+    /// labels added.
+    pub async fn apply_after_ocr(&self, document: &Document) -> Result<usize> {
+        let rules = self.db.get_enabled_label_rules(Some(document.user_id)).await?;
+        let added = self
+            .apply(document, document_text(document), &rules, &mut HashMap::new())
+            .await?;
+        if added > 0 {
+            info!("Labeled document {} by {} rules", document.id, added);
+        }
+        Ok(added)
+    }
+
+    /// Apply the enabled rules to existing documents, e.g. after adding or changing
+    /// rules: those of one user, or of everyone when `None`, and only the rule of
+    /// `label_id` when given. Labels are only added, never removed.
+    /// Returns the number of documents checked and labels added.
+    pub async fn backfill(&self, user_id: Option<Uuid>, label_id: Option<Uuid>) -> Result<(i64, i64)> {
+        let mut rules_by_user: HashMap<Uuid, Vec<LabelRule>> = HashMap::new();
+        for rule in self.db.get_enabled_label_rules(user_id).await? {
+            if label_id.is_none_or(|label_id| rule.label_id == label_id) {
+                rules_by_user.entry(rule.user_id).or_default().push(rule);
+            }
+        }
+        if rules_by_user.is_empty() {
+            return Ok((0, 0));
+        }
+
+        let mut source_names = HashMap::new();
+        let mut checked = 0;
+        let mut added = 0;
+        let mut after = None;
+
+        loop {
+            let documents = self
+                .db
+                .get_documents_for_label_rules(user_id, after, BACKFILL_BATCH_SIZE)
+                .await?;
+            let Some(last) = documents.last() else {
+                break;
+            };
+            after = Some(last.id);
+
+            for document in &documents {
+                let Some(rules) = rules_by_user.get(&document.user_id) else {
+                    continue;
+                };
+                checked += 1;
+                added += self
+                    .apply(document, document_text(document), rules, &mut source_names)
+                    .await? as i64;
+            }
+        }
+
+        Ok((checked, added))
+    }
+
+    async fn apply(
+        &self,
+        document: &Document,
+        text: Option<&str>,
+        rules: &[LabelRule],
+        source_names: &mut HashMap<Uuid, Vec<String>>,
+    ) -> Result<usize> {
+        if rules.is_empty() {
+            return Ok(0);
+        }
+
+        let subject = LabelRuleSubject {
+            text,
+            mime_type: &document.mime_type,
+            sources: self.sources_of(document, source_names).await,
+        };
+
+        let mut added = 0;
+        for rule in rules.iter().filter(|rule| rule.matches(&subject)) {
+            if self.db.apply_rule_label(document.id, rule.label_id, document.user_id).await? {
+                added += 1;
+            }
+        }
+        if added > 0 {
+            EventService::new(self.db.clone()).publish_labels_changed(document.id).await;
+        }
+        Ok(added)
+    }
+
+    /// How the document arrived, and the name and type of its source, which are
+    /// looked up once per source
+    async fn sources_of(&self, document: &Document, source_names: &mut HashMap<Uuid, Vec<String>>) -> Vec<String> {
+        let mut sources: Vec<String> = document.source_type.iter().cloned().collect();
+        let Some(source_id) = document.source_id else {
+            return sources;
+        };
+
+        let names = match source_names.get(&source_id) {
+            Some(names) => names.clone(),
+            None => {
+                let names = match self.db.get_source(document.user_id, source_id).await {
+                    Ok(Some(source)) => vec![source.name, source.source_type.to_string()],
+                    Ok(None) => Vec::new(),
+                    Err(e) => {
+                        warn!("Failed to load source {} for label rules: {}", source_id, e);
+                        Vec::new()
+                    }
+                };
+                source_names.insert(source_id, names.clone());
+                names
+            }
+        };
+        sources.extend(names);
+        sources
+    }
+}
+
+/// OCR text, or the content of documents that have no OCR text
+fn document_text(document: &Document) -> Option<&str> {
+    let usable = |text: &&str| !text.trim().is_empty();
+    document
+        .ocr_text
+        .as_deref()
+        .filter(usable)
+        .or_else(|| document.content.as_deref().filter(usable))
+}
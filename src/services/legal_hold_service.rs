@@ -0,0 +1,175 @@
+//! Legal holds keep documents exactly as they are for a matter: held documents cannot be
+//! deleted or changed, and a manifest of their hashes and timestamps shows they were not.
+
+use anyhow::Result;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{LegalHold, LegalHoldManifest, ManifestEntry};
+use crate::services::file_service::FileService;
+
+const CSV_COLUMNS: [&str; 11] = [
+    "document_id",
+    "version_number",
+    "filename",
+    "original_filename",
+    "mime_type",
+    "file_size",
+    "sha256",
+    "stored_at",
+    "superseded_at",
+    "added_to_hold_at",
+    "verified",
+];
+
+/// A change refused because the document is on an active legal hold
+#[derive(Debug)]
+pub struct DocumentOnLegalHold {
+    pub document_id: Uuid,
+}
+
+impl std::fmt::Display for DocumentOnLegalHold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Document {} is on legal hold and cannot be changed", self.document_id)
+    }
+}
+
+impl std::error::Error for DocumentOnLegalHold {}
+
+/// Fails with [`DocumentOnLegalHold`] when the document is on an active hold
+pub async fn ensure_not_held(db: &Database, document_id: Uuid) -> Result<()> {
+    if db.is_on_legal_hold(document_id).await? {
+        return Err(DocumentOnLegalHold { document_id }.into());
+    }
+    Ok(())
+}
+
+/// Whether the error is a change refused because of a legal hold
+pub fn is_held_error(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<DocumentOnLegalHold>().is_some()
+}
+
+/// The manifest entries as CSV, one row per stored file
+pub fn manifest_csv(entries: &[ManifestEntry]) -> String {
+    let mut csv = CSV_COLUMNS.join(",");
+    csv.push('\n');
+    for entry in entries {
+        let row = [
+            entry.document_id.to_string(),
+            entry.version_number.map(|n| n.to_string()).unwrap_or_default(),
+            entry.filename.clone(),
+            entry.original_filename.clone(),
+            entry.mime_type.clone(),
+            entry.file_size.to_string(),
+            entry.file_hash.clone().unwrap_or_default(),
+            entry.stored_at.to_rfc3339(),
+            entry.superseded_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            entry.added_to_hold_at.to_rfc3339(),
+            entry.verified.map(|v| v.to_string()).unwrap_or_default(),
+        ];
+        csv.push_str(&row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// SHA-256 of the CSV form of the entries, identifying this exact manifest
+pub fn manifest_hash(entries: &[ManifestEntry]) -> String {
+    format!("{:x}", Sha256::digest(manifest_csv(entries).as_bytes()))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub struct LegalHoldService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl LegalHoldService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// The manifest of the hold's documents. With `verify`, every file is read back
+    /// from storage and its hash compared with the one recorded when it was stored.
+    pub async fn manifest(&self, hold: LegalHold, generated_by: Uuid, verify: bool) -> Result<LegalHoldManifest> {
+        let mut entries = Vec::new();
+        for (mut entry, file_path) in self.db.get_legal_hold_manifest_entries(hold.id).await? {
+            if verify {
+                entry.verified = Some(self.verify_file(&entry, &file_path).await);
+            }
+            entries.push(entry);
+        }
+
+        Ok(LegalHoldManifest {
+            manifest_hash: manifest_hash(&entries),
+            hold,
+            generated_at: Utc::now(),
+            generated_by,
+            entries,
+        })
+    }
+
+    async fn verify_file(&self, entry: &ManifestEntry, file_path: &str) -> bool {
+        let Some(expected) = &entry.file_hash else {
+            return false;
+        };
+        match self.file_service.read_file(file_path).await {
+            Ok(data) => format!("{:x}", Sha256::digest(&data)) == *expected,
+            Err(e) => {
+                warn!("Failed to read {} of held document {}: {}", file_path, entry.document_id, e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(filename: &str, file_hash: &str) -> ManifestEntry {
+        let at = "2024-03-01T12:00:00Z".parse().unwrap();
+        ManifestEntry {
+            document_id: Uuid::nil(),
+            version_number: None,
+            filename: filename.to_string(),
+            original_filename: filename.to_string(),
+            mime_type: "application/pdf".to_string(),
+            file_size: 1024,
+            file_hash: Some(file_hash.to_string()),
+            stored_at: at,
+            superseded_at: None,
+            added_to_hold_at: at,
+            verified: Some(true),
+        }
+    }
+
+    #[test]
+    fn test_manifest_csv() {
+        let csv = manifest_csv(&[entry("contract, signed.pdf", "abc123")]);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), CSV_COLUMNS.join(","));
+        assert_eq!(
+            lines.next().unwrap(),
+            "00000000-0000-0000-0000-000000000000,,\"contract, signed.pdf\",\"contract, signed.pdf\",application/pdf,1024,\
+             abc123,2024-03-01T12:00:00+00:00,,2024-03-01T12:00:00+00:00,true"
+        );
+    }
+
+    #[test]
+    fn test_manifest_hash_covers_hashes() {
+        let original = manifest_hash(&[entry("a.pdf", "abc123")]);
+        assert_eq!(original, manifest_hash(&[entry("a.pdf", "abc123")]));
+        assert_ne!(original, manifest_hash(&[entry("a.pdf", "abc124")]));
+    }
+}
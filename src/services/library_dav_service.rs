@@ -0,0 +1,332 @@
+//! The document library as a read-only WebDAV share (RFC 4918, class 1), so file
+//! managers and network copiers can browse it. Documents appear in virtual folders by
+//! label, correspondent and year, and all of them in one more folder.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use quick_xml::escape::escape;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{LibraryFile, LibraryFolder, LibraryGrouping};
+use crate::utils::conditional::{self, Validators};
+
+/// Where the library is served
+pub const DAV_ROOT: &str = "/dav";
+
+/// A path of the library, decoded
+#[derive(Debug, Clone, PartialEq)]
+pub enum DavPath {
+    Root,
+    Grouping(LibraryGrouping),
+    /// A folder under a grouping other than [`LibraryGrouping::All`]
+    Folder(LibraryGrouping, String),
+    /// A document; `folder` is `None` in [`LibraryGrouping::All`]
+    File { grouping: LibraryGrouping, folder: Option<String>, name: String },
+}
+
+impl DavPath {
+    /// Read a request path under [`DAV_ROOT`]. Paths that cannot exist are `None`.
+    pub fn parse(path: &str) -> Option<Self> {
+        let rest = path.strip_prefix(DAV_ROOT)?;
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+        let segments = rest
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| urlencoding::decode(segment).ok().map(|segment| segment.into_owned()))
+            .collect::<Option<Vec<_>>>()?;
+        if segments.iter().any(|segment| segment == "." || segment == "..") {
+            return None;
+        }
+
+        let Some((first, rest)) = segments.split_first() else {
+            return Some(DavPath::Root);
+        };
+        let grouping = LibraryGrouping::from_folder_name(first)?;
+        match (grouping, rest) {
+            (_, []) => Some(DavPath::Grouping(grouping)),
+            (LibraryGrouping::All, [name]) => Some(DavPath::File { grouping, folder: None, name: name.clone() }),
+            (LibraryGrouping::All, _) => None,
+            (_, [folder]) => Some(DavPath::Folder(grouping, folder.clone())),
+            (_, [folder, name]) => Some(DavPath::File { grouping, folder: Some(folder.clone()), name: name.clone() }),
+            _ => None,
+        }
+    }
+}
+
+/// A resource listed in a PROPFIND response
+#[derive(Debug, Clone, PartialEq)]
+pub struct DavEntry {
+    pub href: String,
+    pub name: String,
+    pub collection: bool,
+    pub size: Option<i64>,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub modified_at: Option<DateTime<Utc>>,
+}
+
+impl DavEntry {
+    fn collection(segments: &[&str], name: &str, modified_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            href: href(segments, true),
+            name: name.to_string(),
+            collection: true,
+            size: None,
+            content_type: None,
+            etag: None,
+            created_at: None,
+            modified_at,
+        }
+    }
+
+    fn file(segments: &[&str], name: &str, file: &LibraryFile) -> Self {
+        let mut path = segments.to_vec();
+        path.push(name);
+        Self {
+            href: href(&path, false),
+            name: name.to_string(),
+            collection: false,
+            size: Some(file.file_size),
+            content_type: Some(file.mime_type.clone()),
+            etag: Validators::for_content(file.file_hash.as_deref(), None, file.updated_at).etag,
+            created_at: Some(file.created_at),
+            modified_at: Some(file.updated_at),
+        }
+    }
+}
+
+/// A label or correspondent name as a single path segment
+pub fn segment_name(name: &str) -> String {
+    let name: String = name.trim().chars().map(|c| if c == '/' || c == '\\' { '-' } else { c }).collect();
+    if name.is_empty() || name == "." || name == ".." {
+        "_".to_string()
+    } else {
+        name
+    }
+}
+
+/// Names of the files of a folder. Documents sharing a filename get the start of
+/// their id before the extension, so every name stays the same between listings.
+pub fn file_names(files: Vec<LibraryFile>) -> Vec<(String, LibraryFile)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for file in &files {
+        *counts.entry(segment_name(&file.original_filename)).or_default() += 1;
+    }
+    files
+        .into_iter()
+        .map(|file| {
+            let name = segment_name(&file.original_filename);
+            if counts[&name] < 2 {
+                return (name, file);
+            }
+            let id = file.id.simple().to_string();
+            let name = match name.rsplit_once('.') {
+                Some((stem, extension)) if !stem.is_empty() => format!("{} ({}).{}", stem, &id[..8], extension),
+                _ => format!("{} ({})", name, &id[..8]),
+            };
+            (name, file)
+        })
+        .collect()
+}
+
+/// URL path of a resource, each segment percent-encoded
+pub fn href(segments: &[&str], collection: bool) -> String {
+    let mut href = DAV_ROOT.to_string();
+    for segment in segments {
+        href.push('/');
+        href.push_str(&urlencoding::encode(segment));
+    }
+    if collection {
+        href.push('/');
+    }
+    href
+}
+
+/// The resource at `path` and, with `depth` 1, its members. `None` when it does not exist.
+pub async fn list(db: &Database, user_id: Uuid, path: &DavPath, depth: u8) -> Result<Option<Vec<DavEntry>>> {
+    let mut entries = Vec::new();
+    match path {
+        DavPath::Root => {
+            entries.push(DavEntry::collection(&[], "Library", None));
+            if depth > 0 {
+                for grouping in LibraryGrouping::ALL {
+                    entries.push(DavEntry::collection(&[grouping.folder_name()], grouping.folder_name(), None));
+                }
+            }
+        }
+        DavPath::Grouping(grouping) => {
+            let name = grouping.folder_name();
+            entries.push(DavEntry::collection(&[name], name, None));
+            if depth > 0 && *grouping == LibraryGrouping::All {
+                for (file_name, file) in file_names(db.get_library_files(user_id, LibraryFolder::All).await?) {
+                    entries.push(DavEntry::file(&[name], &file_name, &file));
+                }
+            } else if depth > 0 {
+                for folder in db.get_library_folders(user_id, *grouping).await? {
+                    let folder_name = segment_name(&folder.name);
+                    entries.push(DavEntry::collection(&[name, &folder_name], &folder_name, Some(folder.modified_at)));
+                }
+            }
+        }
+        DavPath::Folder(grouping, name) => {
+            let Some((folder, modified_at)) = find_folder(db, user_id, *grouping, name).await? else {
+                return Ok(None);
+            };
+            let segments = [grouping.folder_name(), name.as_str()];
+            entries.push(DavEntry::collection(&segments, name, Some(modified_at)));
+            if depth > 0 {
+                for (file_name, file) in file_names(db.get_library_files(user_id, folder).await?) {
+                    entries.push(DavEntry::file(&segments, &file_name, &file));
+                }
+            }
+        }
+        DavPath::File { grouping, folder, name } => {
+            let Some(file) = find_file(db, user_id, *grouping, folder.as_deref(), name).await? else {
+                return Ok(None);
+            };
+            let mut segments = vec![grouping.folder_name()];
+            segments.extend(folder.as_deref());
+            entries.push(DavEntry::file(&segments, name, &file));
+        }
+    }
+    Ok(Some(entries))
+}
+
+/// The document a file path names, if it is in that folder
+pub async fn find_file(
+    db: &Database,
+    user_id: Uuid,
+    grouping: LibraryGrouping,
+    folder: Option<&str>,
+    name: &str,
+) -> Result<Option<LibraryFile>> {
+    let folder = match folder {
+        Some(folder) => match find_folder(db, user_id, grouping, folder).await? {
+            Some((folder, _)) => folder,
+            None => return Ok(None),
+        },
+        None => LibraryFolder::All,
+    };
+    Ok(file_names(db.get_library_files(user_id, folder).await?)
+        .into_iter()
+        .find(|(file_name, _)| file_name == name)
+        .map(|(_, file)| file))
+}
+
+async fn find_folder(
+    db: &Database,
+    user_id: Uuid,
+    grouping: LibraryGrouping,
+    name: &str,
+) -> Result<Option<(LibraryFolder, DateTime<Utc>)>> {
+    Ok(db
+        .get_library_folders(user_id, grouping)
+        .await?
+        .into_iter()
+        .find(|folder| segment_name(&folder.name) == name)
+        .map(|folder| (folder.folder, folder.modified_at)))
+}
+
+/// A 207 Multi-Status body with the properties of each entry
+pub fn multistatus(entries: &[DavEntry]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">\n");
+    for entry in entries {
+        xml.push_str("<D:response>\n");
+        xml.push_str(&format!("<D:href>{}</D:href>\n", escape(entry.href.as_str())));
+        xml.push_str("<D:propstat>\n<D:prop>\n");
+        xml.push_str(&format!("<D:displayname>{}</D:displayname>\n", escape(entry.name.as_str())));
+        if entry.collection {
+            xml.push_str("<D:resourcetype><D:collection/></D:resourcetype>\n");
+        } else {
+            xml.push_str("<D:resourcetype/>\n");
+        }
+        if let Some(size) = entry.size {
+            xml.push_str(&format!("<D:getcontentlength>{}</D:getcontentlength>\n", size));
+        }
+        if let Some(content_type) = &entry.content_type {
+            xml.push_str(&format!("<D:getcontenttype>{}</D:getcontenttype>\n", escape(content_type.as_str())));
+        }
+        if let Some(etag) = &entry.etag {
+            xml.push_str(&format!("<D:getetag>{}</D:getetag>\n", escape(etag.as_str())));
+        }
+        if let Some(created_at) = entry.created_at {
+            xml.push_str(&format!("<D:creationdate>{}</D:creationdate>\n", created_at.to_rfc3339()));
+        }
+        if let Some(modified_at) = entry.modified_at {
+            xml.push_str(&format!(
+                "<D:getlastmodified>{}</D:getlastmodified>\n",
+                conditional::format_http_date(modified_at)
+            ));
+        }
+        xml.push_str("</D:prop>\n<D:status>HTTP/1.1 200 OK</D:status>\n</D:propstat>\n</D:response>\n");
+    }
+    xml.push_str("</D:multistatus>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, id: u128) -> LibraryFile {
+        LibraryFile {
+            id: Uuid::from_u128(id),
+            original_filename: name.to_string(),
+            file_size: 1024,
+            mime_type: "application/pdf".to_string(),
+            file_hash: Some("abc".to_string()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_parse_dav_paths() {
+        assert_eq!(DavPath::parse("/dav"), Some(DavPath::Root));
+        assert_eq!(DavPath::parse("/dav/"), Some(DavPath::Root));
+        assert_eq!(DavPath::parse("/dav/Years/"), Some(DavPath::Grouping(LibraryGrouping::Years)));
+        assert_eq!(
+            DavPath::parse("/dav/Labels/Tax%20Returns/return%202024.pdf"),
+            Some(DavPath::File {
+                grouping: LibraryGrouping::Labels,
+                folder: Some("Tax Returns".to_string()),
+                name: "return 2024.pdf".to_string(),
+            })
+        );
+        assert_eq!(
+            DavPath::parse("/dav/All%20Documents/scan.pdf"),
+            Some(DavPath::File { grouping: LibraryGrouping::All, folder: None, name: "scan.pdf".to_string() })
+        );
+        assert_eq!(DavPath::parse("/dav/All%20Documents/a/b.pdf"), None);
+        assert_eq!(DavPath::parse("/dav/Labels/../Years"), None);
+        assert_eq!(DavPath::parse("/dav/Elsewhere"), None);
+        assert_eq!(DavPath::parse("/davx"), None);
+    }
+
+    #[test]
+    fn test_duplicate_filenames_get_the_document_id() {
+        let names: Vec<String> = file_names(vec![
+            file("invoice.pdf", 0xabcdef01_0000_0000_0000_000000000000),
+            file("invoice.pdf", 0x12345678_0000_0000_0000_000000000000),
+            file("a/b.pdf", 3),
+        ])
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+        assert_eq!(names, vec!["invoice (abcdef01).pdf", "invoice (12345678).pdf", "a-b.pdf"]);
+        assert_eq!(segment_name(" .. "), "_");
+    }
+
+    #[test]
+    fn test_multistatus_escapes_names() {
+        let xml = multistatus(&[DavEntry::file(&["Labels", "R&D"], "plan <v2>.pdf", &file("plan <v2>.pdf", 1))]);
+        assert!(xml.contains("<D:href>/dav/Labels/R%26D/plan%20%3Cv2%3E.pdf</D:href>"));
+        assert!(xml.contains("<D:displayname>plan &lt;v2&gt;.pdf</D:displayname>"));
+        assert!(xml.contains("<D:getetag>&quot;abc&quot;</D:getetag>"));
+    }
+}
@@ -0,0 +1,230 @@
+//! Opt-in debug log of what analysis runs send to the LLM and what comes back. A run
+//! is logged when `LLM_DEBUG_LOG` is on, or when an admin asks for it; the requests
+//! made while it runs are stored with it, with personal data redacted as configured in
+//! `LLM_DEBUG_LOG_REDACT`, and runs are removed after `LLM_DEBUG_LOG_RETENTION_DAYS`.
+
+use std::future::Future;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::llm_service::PromptTemplate;
+use crate::db::Database;
+use crate::models::PiiKind;
+
+const ALL_KINDS: [PiiKind; 5] = [PiiKind::Ssn, PiiKind::Iban, PiiKind::CreditCard, PiiKind::Email, PiiKind::Phone];
+const DEFAULT_RETENTION_DAYS: i64 = 7;
+/// Pause between removals of runs past the retention period
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+pub struct DebugLogConfig {
+    pub enabled: bool,
+    pub redact: Vec<PiiKind>,
+    pub retention_days: i64,
+}
+
+pub static CONFIG: Lazy<DebugLogConfig> = Lazy::new(|| DebugLogConfig {
+    enabled: std::env::var("LLM_DEBUG_LOG")
+        .map(|v| matches!(v.to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false),
+    redact: parse_redaction(&std::env::var("LLM_DEBUG_LOG_REDACT").unwrap_or_default()),
+    retention_days: std::env::var("LLM_DEBUG_LOG_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_RETENTION_DAYS),
+});
+
+tokio::task_local! {
+    static RUN: Uuid;
+}
+
+/// Kinds of personal data to redact from a comma-separated list of kinds, `all` or
+/// `none`. Unset or unreadable values redact everything, so a typo never stores
+/// personal data.
+pub fn parse_redaction(value: &str) -> Vec<PiiKind> {
+    match value.trim().to_lowercase().as_str() {
+        "" | "all" => ALL_KINDS.to_vec(),
+        "none" => Vec::new(),
+        list => {
+            let kinds: Option<Vec<PiiKind>> = list
+                .split(',')
+                .filter(|name| !name.trim().is_empty())
+                .map(PiiKind::from_name)
+                .collect();
+            kinds.unwrap_or_else(|| {
+                warn!("Unknown kind in LLM_DEBUG_LOG_REDACT '{}', redacting all personal data", value);
+                ALL_KINDS.to_vec()
+            })
+        }
+    }
+}
+
+fn kind_name(kind: PiiKind) -> &'static str {
+    match kind {
+        PiiKind::Ssn => "ssn",
+        PiiKind::Iban => "iban",
+        PiiKind::CreditCard => "credit_card",
+        PiiKind::Email => "email",
+        PiiKind::Phone => "phone",
+    }
+}
+
+/// How a run's text is redacted, as stored with it
+pub fn redaction_label(kinds: &[PiiKind]) -> String {
+    if kinds.is_empty() {
+        return "none".to_string();
+    }
+    kinds.iter().map(|kind| kind_name(*kind)).collect::<Vec<_>>().join(",")
+}
+
+/// The text with personal data of the given kinds replaced by `[kind]`
+pub fn redact(text: &str, kinds: &[PiiKind]) -> String {
+    if kinds.is_empty() {
+        return text.to_string();
+    }
+    let mut redacted = String::with_capacity(text.len());
+    let mut end = 0;
+    for found in crate::ocr::pii::detect(text).into_iter().filter(|m| kinds.contains(&m.kind)) {
+        redacted.push_str(&text[end..found.start]);
+        redacted.push_str(&format!("[{}]", kind_name(found.kind)));
+        end = found.end;
+    }
+    redacted.push_str(&text[end..]);
+    redacted
+}
+
+/// Start logging a run of `task` when the log is on or the run is `forced`. The run
+/// id, for [`in_run`] and [`finish_run`]; None when the run is not logged.
+pub async fn start_run(pool: &PgPool, document_id: Option<Uuid>, task: &str, model: &str, forced: bool) -> Option<Uuid> {
+    if !CONFIG.enabled && !forced {
+        return None;
+    }
+    let result = sqlx::query_scalar(
+        r#"INSERT INTO llm_debug_runs (document_id, task, model, redaction)
+           VALUES ($1, $2, $3, $4)
+           RETURNING id"#,
+    )
+    .bind(document_id)
+    .bind(task)
+    .bind(model)
+    .bind(redaction_label(&CONFIG.redact))
+    .fetch_one(pool)
+    .await;
+    match result {
+        Ok(run_id) => Some(run_id),
+        Err(e) => {
+            warn!("Failed to start LLM debug run for {}: {}", task, e);
+            None
+        }
+    }
+}
+
+/// Run the work of a logged run, logging the LLM requests it makes
+pub async fn in_run<F: Future>(run_id: Uuid, work: F) -> F::Output {
+    RUN.scope(run_id, work).await
+}
+
+/// The logged run the running work belongs to, if any
+pub fn current_run() -> Option<Uuid> {
+    RUN.try_with(|run_id| *run_id).ok()
+}
+
+/// Record how a run ended: the graph version it produced, or why it failed
+pub async fn finish_run(pool: &PgPool, run_id: Uuid, graph_version_id: Option<Uuid>, error: Option<&str>) {
+    let result = sqlx::query(
+        "UPDATE llm_debug_runs SET graph_version_id = $2, error = $3, finished_at = NOW() WHERE id = $1",
+    )
+    .bind(run_id)
+    .bind(graph_version_id)
+    .bind(error.map(|e| redact(e, &CONFIG.redact)))
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        warn!("Failed to finish LLM debug run {}: {}", run_id, e);
+    }
+}
+
+/// Log a request to the LLM and its answer with the current run, if any
+pub async fn record_exchange(
+    pool: &PgPool,
+    template: PromptTemplate,
+    system_prompt: &str,
+    prompt: &str,
+    answer: Result<&str, &str>,
+    cached: bool,
+    started: std::time::Instant,
+) {
+    let Some(run_id) = current_run() else {
+        return;
+    };
+    let kinds = &CONFIG.redact;
+    let (response, error) = match answer {
+        Ok(response) => (Some(redact(response, kinds)), None),
+        Err(error) => (None, Some(redact(error, kinds))),
+    };
+    let result = sqlx::query(
+        r#"INSERT INTO llm_debug_exchanges
+               (run_id, prompt_version, system_prompt, prompt, response, error, cached, duration_ms)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+    )
+    .bind(run_id)
+    .bind(template.version)
+    .bind(redact(system_prompt, kinds))
+    .bind(redact(prompt, kinds))
+    .bind(response)
+    .bind(error)
+    .bind(cached)
+    .bind(started.elapsed().as_millis() as i64)
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        warn!("Failed to log LLM request of debug run {}: {}", run_id, e);
+    }
+}
+
+/// Remove runs past the retention period, hourly
+pub async fn run_cleanup(db: Database) {
+    let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        match db.prune_llm_debug_runs(CONFIG.retention_days).await {
+            Ok(removed) if removed > 0 => info!("Removed {} LLM debug run(s) past retention", removed),
+            Ok(_) => {}
+            Err(e) => error!("Failed to remove old LLM debug runs: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_redaction() {
+        assert_eq!(parse_redaction(""), ALL_KINDS.to_vec());
+        assert_eq!(parse_redaction("None"), Vec::new());
+        assert_eq!(parse_redaction("email, phone"), vec![PiiKind::Email, PiiKind::Phone]);
+        assert_eq!(parse_redaction("email,passport"), ALL_KINDS.to_vec());
+        assert_eq!(redaction_label(&[PiiKind::Email, PiiKind::CreditCard]), "email,credit_card");
+        assert_eq!(redaction_label(&[]), "none");
+    }
+
+    #[test]
+    fn test_redact() {
+        let text = "Contact jane.doe@example.com or +49 30 1234 5678.";
+        assert_eq!(redact(text, &ALL_KINDS), "Contact [email] or [phone].");
+        assert_eq!(redact(text, &[PiiKind::Email]), "Contact [email] or +49 30 1234 5678.");
+        assert_eq!(redact(text, &[]), text);
+    }
+
+    #[tokio::test]
+    async fn test_requests_belong_to_the_run() {
+        let run_id = Uuid::new_v4();
+        assert_eq!(current_run(), None);
+        assert_eq!(in_run(run_id, async { current_run() }).await, Some(run_id));
+    }
+}
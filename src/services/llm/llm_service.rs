@@ -5,8 +5,33 @@ use serde::{Deserialize, Serialize};
 use crate::models::document::Document;
 use sqlx::Row;
 use reqwest::Client;
+use sha2::{Digest, Sha256};
 use std::env;
 
+use crate::models::{ContentDestination, OutboundScope};
+use crate::monitoring::telemetry::inject_trace_context;
+use crate::services::{data_residency, outbound_limits, processing_usage};
+use super::debug_log;
+
+/// Characters of a document's text sent for knowledge-graph extraction
+pub const GRAPH_EXTRACTION_MAX_CHARS: usize = 4000;
+
+/// A prompt whose answers are cached. Raise `version` whenever the prompt's wording
+/// changes, so that answers to the old wording are no longer used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptTemplate {
+    pub task: &'static str,
+    pub version: i32,
+}
+
+impl PromptTemplate {
+    pub const fn new(task: &'static str, version: i32) -> Self {
+        Self { task, version }
+    }
+}
+
+const GRAPH_EXTRACTION_PROMPT: PromptTemplate = PromptTemplate::new("graph_extraction", 1);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GraphNode {
     pub label: String,
@@ -22,10 +47,26 @@ pub struct GraphEdge {
     pub properties: serde_json::Value,
 }
 
+/// A dated event mentioned in the text, e.g. a contract signature or a payment due date
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphEvent {
+    /// `YYYY-MM-DD`, or `YYYY-MM` / `YYYY` when that is all the text says
+    pub date: String,
+    pub description: String,
+    /// Names of the nodes involved
+    #[serde(default)]
+    pub entities: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GraphData {
     pub nodes: Vec<GraphNode>,
     pub edges: Vec<GraphEdge>,
+    #[serde(default)]
+    pub events: Vec<GraphEvent>,
+    /// Whether the graph is an earlier answer of the model for the same text
+    #[serde(skip)]
+    pub cached: bool,
 }
 
 #[derive(Clone)]
@@ -35,6 +76,13 @@ pub struct LLMService {
     api_key: Option<String>,
     api_url: String,
     model: String,
+    vision_model: Option<String>,
+    /// Prices per 1,000 input and output tokens, for cost estimates
+    token_prices: Option<(f64, f64)>,
+    /// Whether completions may be answered from the response cache
+    use_cache: bool,
+    /// Whether analysis runs are logged to the debug log even when it is off
+    debug_log: bool,
 }
 
 impl LLMService {
@@ -42,6 +90,16 @@ impl LLMService {
         let api_key = env::var("LLM_API_KEY").ok();
         let api_url = env::var("LLM_API_URL").unwrap_or_else(|_| "https://api.openai.com/v1/chat/completions".to_string());
         let model = env::var("LLM_MODEL").unwrap_or_else(|_| "gpt-3.5-turbo".to_string());
+        let vision_model = env::var("LLM_VISION_MODEL").ok().filter(|m| !m.trim().is_empty());
+        let input_price = env::var("LLM_INPUT_COST_PER_1K_TOKENS").ok().and_then(|v| v.parse::<f64>().ok());
+        let output_price = env::var("LLM_OUTPUT_COST_PER_1K_TOKENS").ok().and_then(|v| v.parse::<f64>().ok());
+        let token_prices = match (input_price, output_price) {
+            (None, None) => None,
+            (input, output) => Some((input.unwrap_or(0.0), output.unwrap_or(0.0))),
+        };
+        let use_cache = env::var("LLM_CACHE_ENABLED")
+            .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+            .unwrap_or(true);
 
         Self {
             pool,
@@ -49,10 +107,134 @@ impl LLMService {
             api_key,
             api_url,
             model,
+            vision_model,
+            token_prices,
+            use_cache,
+            debug_log: false,
         }
     }
 
+    /// Ask the model again instead of using cached answers. The new answers still
+    /// replace the cached ones.
+    pub fn bypass_cache(mut self) -> Self {
+        self.use_cache = false;
+        self
+    }
+
+    /// Log the analysis runs of this service to the LLM debug log, even when it is off
+    pub fn debug_log(mut self) -> Self {
+        self.debug_log = true;
+        self
+    }
+
+    /// The chat model used for text analysis
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Configured prices per 1,000 input and output tokens; a price that is not set
+    /// counts as free
+    pub fn token_prices(&self) -> Option<(f64, f64)> {
+        self.token_prices
+    }
+
+    /// Whether a vision-capable model is configured for image descriptions.
+    /// No API key is required so local OpenAI-compatible servers (e.g. LLaVA via Ollama) work.
+    pub fn vision_enabled(&self) -> bool {
+        self.vision_model.is_some()
+    }
+
+    pub fn vision_model(&self) -> Option<&str> {
+        self.vision_model.as_deref()
+    }
+
+    /// Ask the vision model to describe a PNG image. Returns `Ok(None)` when no
+    /// vision model is configured.
+    #[tracing::instrument(name = "llm.describe_image", skip_all, fields(model = self.vision_model.as_deref(), image_bytes = png_data.len()))]
+    pub async fn describe_image(&self, png_data: &[u8], prompt: &str) -> Result<Option<String>, String> {
+        let Some(vision_model) = self.vision_model.as_ref() else {
+            return Ok(None);
+        };
+
+        self.authorize_transmission(png_data.len() + prompt.len()).await?;
+        let _permit = outbound_limits::acquire(OutboundScope::Llm).await;
+        let started = std::time::Instant::now();
+        let result = self.request_image_description(vision_model, png_data, prompt).await;
+        record_llm_call("llm.describe_image", started, vision_model, png_data.len(), result.is_ok());
+        result.map(Some)
+    }
+
+    async fn request_image_description(&self, vision_model: &str, png_data: &[u8], prompt: &str) -> Result<String, String> {
+        use base64ct::Encoding;
+        let started = std::time::Instant::now();
+        let image_url = format!("data:image/png;base64,{}", base64ct::Base64::encode_string(png_data));
+
+        let request_body = serde_json::json!({
+            "model": vision_model,
+            "messages": [
+                {
+                    "role": "user",
+                    "content": [
+                        {"type": "text", "text": prompt},
+                        {"type": "image_url", "image_url": {"url": image_url}}
+                    ]
+                }
+            ],
+            "temperature": 0.0
+        });
+
+        let mut request = inject_trace_context(self.client.post(&self.api_url))
+            .header("Content-Type", "application/json");
+        if let Some(api_key) = self.api_key.as_ref() {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let response = request
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request to vision LLM: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Vision LLM API returned error: {}", response.status()));
+        }
+
+        let response_json: serde_json::Value = response.json().await
+            .map_err(|e| format!("Failed to parse vision LLM response: {}", e))?;
+        crate::monitoring::prometheus::record_llm_usage(vision_model, &response_json);
+        self.record_document_usage(vision_model, &response_json, started).await;
+
+        let description = response_json["choices"][0]["message"]["content"].as_str()
+            .ok_or("Invalid response format from vision LLM")?
+            .trim()
+            .to_string();
+
+        Ok(description)
+    }
+
     pub async fn analyze_document(&self, document_id: Uuid) -> Result<GraphData, String> {
+        let run_id = if self.api_key.is_some() {
+            debug_log::start_run(&self.pool, Some(document_id), GRAPH_EXTRACTION_PROMPT.task, &self.model, self.debug_log).await
+        } else {
+            None
+        };
+        let Some(run_id) = run_id else {
+            return self.extract_graph(document_id).await.map(|(graph, _)| graph);
+        };
+
+        let result = debug_log::in_run(run_id, self.extract_graph(document_id)).await;
+        debug_log::finish_run(
+            &self.pool,
+            run_id,
+            result.as_ref().ok().map(|(_, version_id)| *version_id),
+            result.as_ref().err().map(String::as_str),
+        )
+        .await;
+        result.map(|(graph, _)| graph)
+    }
+
+    /// Extract and store the document's graph; the graph and its version id
+    async fn extract_graph(&self, document_id: Uuid) -> Result<(GraphData, Uuid), String> {
         // 1. Fetch document content
         let doc: Document = sqlx::query_as::<_, Document>(
             "SELECT * FROM documents WHERE id = $1"
@@ -73,31 +255,140 @@ impl LLMService {
         };
 
         // 3. Store graph data
-        self.store_graph_data(document_id, &graph_data).await?;
+        let version_id = self.store_graph_data(document_id, &graph_data).await?;
 
-        Ok(graph_data)
+        Ok((graph_data, version_id))
     }
 
-    async fn call_llm_api(&self, content: &str) -> Result<GraphData, String> {
-        let prompt = format!(
-            "Extract entities (nodes) and relationships (edges) from the following text to build a knowledge graph. \
-            Return ONLY a JSON object with two keys: 'nodes' (list of objects with 'label', 'name', 'properties') \
-            and 'edges' (list of objects with 'source' (name), 'target' (name), 'relationship', 'properties'). \
-            Text: {}",
-            content.chars().take(4000).collect::<String>() // Truncate to avoid token limits for now
-        );
+    /// Whether a text chat model can be called
+    pub fn chat_enabled(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    /// Whether a chat or vision model is configured
+    pub fn configured(&self) -> bool {
+        self.chat_enabled() || self.vision_enabled()
+    }
+
+    /// Endpoint completions are requested from
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    /// Check with the data residency policy that the request may go out, recording it
+    /// against the document it is made for
+    async fn authorize_transmission(&self, bytes: usize) -> Result<(), String> {
+        let document = processing_usage::current_document();
+        data_residency::authorize(&self.pool, ContentDestination::Llm, &self.api_url, document.as_slice(), bytes).await
+    }
+
+    /// Check that the API answers at all, without running a model. Any response short
+    /// of a server error counts, since the endpoint only accepts completion requests.
+    pub async fn check_reachable(&self, timeout: std::time::Duration) -> Result<(), String> {
+        let response = self.client.get(&self.api_url)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach LLM API: {}", e))?;
+
+        if response.status().is_server_error() {
+            return Err(format!("LLM API returned error: {}", response.status()));
+        }
+        Ok(())
+    }
+
+    /// Send a single prompt to the chat model and return its answer with any
+    /// markdown code fences stripped. The same prompt of the same template version is
+    /// answered from the cache.
+    pub async fn complete(&self, template: PromptTemplate, system_prompt: &str, prompt: &str) -> Result<String, String> {
+        self.complete_with_cache(template, system_prompt, prompt).await.map(|(answer, _)| answer)
+    }
+
+    /// The answer, and whether it came from the cache
+    #[tracing::instrument(name = "llm.complete", skip_all, fields(model = %self.model, task = template.task))]
+    async fn complete_with_cache(
+        &self,
+        template: PromptTemplate,
+        system_prompt: &str,
+        prompt: &str,
+    ) -> Result<(String, bool), String> {
+        let api_key = self.api_key.as_ref().ok_or("LLM API key is not configured")?;
+        let content_hash = prompt_hash(system_prompt, prompt);
+        let started = std::time::Instant::now();
 
+        if self.use_cache {
+            match self.cached_response(template, &content_hash).await {
+                Ok(Some(answer)) => {
+                    tracing::debug!("Answered {} v{} prompt from the LLM cache", template.task, template.version);
+                    debug_log::record_exchange(&self.pool, template, system_prompt, prompt, Ok(&answer), true, started).await;
+                    return Ok((answer, true));
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read the LLM response cache: {}", e),
+            }
+        }
+
+        self.authorize_transmission(system_prompt.len() + prompt.len()).await?;
+        let permit = outbound_limits::acquire(OutboundScope::Llm).await;
+        let started = std::time::Instant::now();
+        let result = self.request_completion(api_key, system_prompt, prompt).await;
+        drop(permit);
+        record_llm_call("llm.complete", started, &self.model, system_prompt.len() + prompt.len(), result.is_ok());
+        debug_log::record_exchange(&self.pool, template, system_prompt, prompt, result.as_deref().map_err(String::as_str), false, started).await;
+
+        let answer = result?;
+        if let Err(e) = self.store_response(template, &content_hash, &answer).await {
+            tracing::warn!("Failed to cache LLM response: {}", e);
+        }
+        Ok((answer, false))
+    }
+
+    async fn cached_response(&self, template: PromptTemplate, content_hash: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"UPDATE llm_response_cache
+               SET hit_count = hit_count + 1, last_hit_at = NOW()
+               WHERE task = $1 AND prompt_version = $2 AND model = $3 AND content_hash = $4
+               RETURNING response"#
+        )
+        .bind(template.task)
+        .bind(template.version)
+        .bind(&self.model)
+        .bind(content_hash)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn store_response(&self, template: PromptTemplate, content_hash: &str, response: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"INSERT INTO llm_response_cache (task, prompt_version, model, content_hash, response)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (task, prompt_version, model, content_hash)
+               DO UPDATE SET response = EXCLUDED.response, created_at = NOW()"#
+        )
+        .bind(template.task)
+        .bind(template.version)
+        .bind(&self.model)
+        .bind(content_hash)
+        .bind(response)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn request_completion(&self, api_key: &str, system_prompt: &str, prompt: &str) -> Result<String, String> {
+        let started = std::time::Instant::now();
         let request_body = serde_json::json!({
             "model": self.model,
             "messages": [
-                {"role": "system", "content": "You are a helpful assistant that extracts knowledge graphs from text."},
+                {"role": "system", "content": system_prompt},
                 {"role": "user", "content": prompt}
             ],
             "temperature": 0.0
         });
 
-        let response = self.client.post(&self.api_url)
-            .header("Authorization", format!("Bearer {}", self.api_key.as_ref().unwrap()))
+        let response = inject_trace_context(self.client.post(&self.api_url))
+            .header("Authorization", format!("Bearer {}", api_key))
             .header("Content-Type", "application/json")
             .json(&request_body)
             .send()
@@ -110,19 +401,67 @@ impl LLMService {
 
         let response_json: serde_json::Value = response.json().await
             .map_err(|e| format!("Failed to parse LLM response: {}", e))?;
+        crate::monitoring::prometheus::record_llm_usage(&self.model, &response_json);
+        self.record_document_usage(&self.model, &response_json, started).await;
 
         let content_str = response_json["choices"][0]["message"]["content"].as_str()
             .ok_or("Invalid response format from LLM")?;
 
         // Clean up markdown code blocks if present
-        let clean_json = content_str.trim()
+        Ok(content_str.trim()
             .trim_start_matches("```json")
             .trim_start_matches("```")
             .trim_end_matches("```")
-            .trim();
+            .trim()
+            .to_string())
+    }
 
-        let graph_data: GraphData = serde_json::from_str(clean_json)
+    /// Record a request made for a document against it, with the tokens the API reported
+    async fn record_document_usage(&self, model: &str, response: &serde_json::Value, started: std::time::Instant) {
+        let Some(document_id) = processing_usage::current_document() else {
+            return;
+        };
+        let (prompt_tokens, completion_tokens) = processing_usage::llm_tokens(response);
+        let result = sqlx::query(
+            r#"INSERT INTO document_processing_usage
+                   (document_id, kind, seconds, model, prompt_tokens, completion_tokens, cost)
+               VALUES ($1, 'llm', $2, $3, $4, $5, $6)"#
+        )
+        .bind(document_id)
+        .bind(started.elapsed().as_secs_f64())
+        .bind(model)
+        .bind(prompt_tokens)
+        .bind(completion_tokens)
+        .bind(processing_usage::llm_cost(prompt_tokens, completion_tokens, self.token_prices))
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("Failed to record LLM usage of document {}: {}", document_id, e);
+        }
+    }
+
+    async fn call_llm_api(&self, content: &str) -> Result<GraphData, String> {
+        let prompt = format!(
+            "Extract entities (nodes), relationships (edges) and dated events from the following text to build a knowledge graph. \
+            Return ONLY a JSON object with three keys: 'nodes' (list of objects with 'label', 'name', 'properties'), \
+            'edges' (list of objects with 'source' (name), 'target' (name), 'relationship', 'properties') \
+            and 'events' (list of objects with 'date' (YYYY-MM-DD, or YYYY-MM or YYYY when the day or month is not given), \
+            'description' (a short sentence such as 'Contract signed') and 'entities' (names of the nodes involved)). \
+            Text: {}",
+            content.chars().take(GRAPH_EXTRACTION_MAX_CHARS).collect::<String>() // Truncate to avoid token limits for now
+        );
+
+        let (clean_json, cached) = self
+            .complete_with_cache(
+                GRAPH_EXTRACTION_PROMPT,
+                "You are a helpful assistant that extracts knowledge graphs from text.",
+                &prompt,
+            )
+            .await?;
+
+        let mut graph_data: GraphData = serde_json::from_str(&clean_json)
             .map_err(|e| format!("Failed to parse GraphData JSON: {}", e))?;
+        graph_data.cached = cached;
 
         Ok(graph_data)
     }
@@ -150,27 +489,44 @@ impl LLMService {
                     properties: serde_json::json!({}),
                 },
             ],
+            events: vec![
+                GraphEvent {
+                    date: "2021-05-03".to_string(),
+                    description: "Employment contract signed".to_string(),
+                    entities: vec!["John Doe".to_string(), "Readur Corp".to_string()],
+                },
+            ],
+            cached: false,
         }
     }
 
-    async fn store_graph_data(&self, document_id: Uuid, data: &GraphData) -> Result<(), String> {
+    async fn store_graph_data(&self, document_id: Uuid, data: &GraphData) -> Result<Uuid, String> {
         let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
 
-        // Clear existing graph data for this document
-        sqlx::query("DELETE FROM document_nodes WHERE document_id = $1")
-            .bind(document_id)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
+        // Keep the graph as a new version; earlier versions stay until they are pruned
+        let version_id: Uuid = sqlx::query_scalar(
+            r#"INSERT INTO document_graph_versions (document_id, version_number, model, node_count, edge_count)
+               SELECT $1, COALESCE(MAX(version_number), 0) + 1, $2, $3, $4
+               FROM document_graph_versions WHERE document_id = $1
+               RETURNING id"#
+        )
+        .bind(document_id)
+        .bind(self.api_key.is_some().then_some(&self.model))
+        .bind(data.nodes.len() as i32)
+        .bind(data.edges.len() as i32)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
 
         // Insert nodes
         let mut node_map = std::collections::HashMap::new();
 
         for node in &data.nodes {
             let row = sqlx::query(
-                "INSERT INTO document_nodes (document_id, label, name, properties) VALUES ($1, $2, $3, $4) RETURNING id"
+                "INSERT INTO document_nodes (document_id, version_id, label, name, properties) VALUES ($1, $2, $3, $4, $5) RETURNING id"
             )
             .bind(document_id)
+            .bind(version_id)
             .bind(&node.label)
             .bind(&node.name)
             .bind(&node.properties)
@@ -188,9 +544,10 @@ impl LLMService {
             let target_id = node_map.get(&edge.target).ok_or(format!("Target node {} not found", edge.target))?;
 
             sqlx::query(
-                "INSERT INTO document_edges (document_id, source_node_id, target_node_id, relationship, properties) VALUES ($1, $2, $3, $4, $5)"
+                "INSERT INTO document_edges (document_id, version_id, source_node_id, target_node_id, relationship, properties) VALUES ($1, $2, $3, $4, $5, $6)"
             )
             .bind(document_id)
+            .bind(version_id)
             .bind(source_id)
             .bind(target_id)
             .bind(&edge.relationship)
@@ -200,7 +557,78 @@ impl LLMService {
             .map_err(|e| e.to_string())?;
         }
 
+        // Events with a date that can't be read are left out of the timeline
+        for event in &data.events {
+            let Some((event_date, precision)) = crate::models::parse_event_date(&event.date) else {
+                continue;
+            };
+            if event.description.trim().is_empty() {
+                continue;
+            }
+            sqlx::query(
+                r#"INSERT INTO document_timeline_events (document_id, version_id, event_date, date_precision, description, entities)
+                   VALUES ($1, $2, $3, $4, $5, $6)"#
+            )
+            .bind(document_id)
+            .bind(version_id)
+            .bind(event_date)
+            .bind(precision.to_string())
+            .bind(event.description.trim())
+            .bind(&event.entities)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+
+        sqlx::query(
+            r#"DELETE FROM document_graph_versions
+               WHERE document_id = $1
+                 AND version_number <= (SELECT MAX(version_number) FROM document_graph_versions WHERE document_id = $1) - $2"#
+        )
+        .bind(document_id)
+        .bind(crate::models::MAX_GRAPH_VERSIONS)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
         tx.commit().await.map_err(|e| e.to_string())?;
-        Ok(())
+        Ok(version_id)
+    }
+}
+
+/// Add a finished LLM request to the slow operation profiler
+fn record_llm_call(name: &str, started: std::time::Instant, model: &str, input_bytes: usize, succeeded: bool) {
+    crate::monitoring::slow_operations::record(
+        crate::monitoring::slow_operations::OperationCategory::Llm,
+        name,
+        started,
+        succeeded,
+        serde_json::json!({
+            "model": model,
+            "input_bytes": input_bytes,
+        }),
+    );
+}
+
+/// Cache key of a prompt: the system prompt and prompt, with a separator so that text
+/// moving from one to the other changes the hash
+fn prompt_hash(system_prompt: &str, prompt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(system_prompt.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(prompt.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_hash() {
+        assert_eq!(prompt_hash("system", "text"), prompt_hash("system", "text"));
+        assert_ne!(prompt_hash("system", "text"), prompt_hash("system", "text2"));
+        assert_ne!(prompt_hash("ab", "c"), prompt_hash("a", "bc"));
+        assert_eq!(prompt_hash("", "").len(), 64);
     }
 }
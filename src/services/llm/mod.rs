@@ -1 +1,2 @@
+pub mod debug_log;
 pub mod llm_service;
@@ -83,78 +83,7 @@ impl LocalFolderService {
                         // Get file metadata
                         match fs::metadata(path) {
                             Ok(metadata) => {
-                                let modified_time = metadata.modified()
-                                    .ok()
-                                    .and_then(|time| {
-                                        let duration = time.duration_since(std::time::UNIX_EPOCH).ok()?;
-                                        DateTime::from_timestamp(duration.as_secs() as i64, 0)
-                                    });
-
-                                // Try to get creation time (not available on all systems)
-                                let created_time = metadata.created()
-                                    .ok()
-                                    .and_then(|time| {
-                                        let duration = time.duration_since(std::time::UNIX_EPOCH).ok()?;
-                                        DateTime::from_timestamp(duration.as_secs() as i64, 0)
-                                    });
-
-                                let file_name = path.file_name()
-                                    .and_then(|name| name.to_str())
-                                    .unwrap_or("unknown")
-                                    .to_string();
-
-                                // Generate a simple hash-based ETag from file path and modification time
-                                let etag = Self::generate_etag(path, &metadata);
-
-                                // Determine MIME type based on extension
-                                let mime_type = Self::get_mime_type(&extension);
-
-                                // Extract file permissions and ownership info
-                                #[cfg(unix)]
-                                let (permissions, owner, group) = {
-                                    use std::os::unix::fs::MetadataExt;
-                                    (
-                                        Some(metadata.mode() & 0o777), // File mode bits (permissions)
-                                        Some(metadata.uid().to_string()), // User ID
-                                        Some(metadata.gid().to_string()), // Group ID
-                                    )
-                                };
-                                
-                                #[cfg(not(unix))]
-                                let (permissions, owner, group) = (None, None, None);
-
-                                // Prepare additional metadata
-                                let mut additional_metadata = serde_json::Map::new();
-                                
-                                #[cfg(unix)]
-                                {
-                                    use std::os::unix::fs::MetadataExt;
-                                    additional_metadata.insert("inode".to_string(), serde_json::Value::Number(metadata.ino().into()));
-                                    additional_metadata.insert("nlinks".to_string(), serde_json::Value::Number(metadata.nlink().into()));
-                                    additional_metadata.insert("device".to_string(), serde_json::Value::Number(metadata.dev().into()));
-                                }
-                                
-                                // Add file attributes
-                                additional_metadata.insert("readonly".to_string(), serde_json::Value::Bool(metadata.permissions().readonly()));
-                                
-                                let file_info = FileIngestionInfo {
-                                    relative_path: path.to_string_lossy().to_string(),
-                                    full_path: path.to_string_lossy().to_string(), // For filesystem, relative and full are the same
-                                    #[allow(deprecated)]
-                                    path: path.to_string_lossy().to_string(),
-                                    name: file_name,
-                                    size: metadata.len() as i64,
-                                    mime_type,
-                                    last_modified: modified_time,
-                                    etag,
-                                    is_directory: false,
-                                    created_at: created_time,
-                                    permissions,
-                                    owner,
-                                    group,
-                                    metadata: if additional_metadata.is_empty() { None } else { Some(serde_json::Value::Object(additional_metadata)) },
-                                };
-
+                                let file_info = Self::file_info(path, &metadata, &extension);
                                 files.push(file_info);
                             }
                             Err(e) => {
@@ -175,6 +104,112 @@ impl LocalFolderService {
         Ok(discovered_files)
     }
 
+    /// The ingestion info of a file reported by a folder watcher, or None when the source
+    /// does not sync it: outside its folders (or below them when not recursive), hidden or
+    /// temporary, or of an unwanted type
+    pub async fn watched_file_info(&self, path: &Path) -> Result<Option<FileIngestionInfo>> {
+        let in_folder = self.config.watch_folders.iter().any(|folder| match path.strip_prefix(folder) {
+            Ok(relative) => self.config.recursive || relative.components().count() == 1,
+            Err(_) => false,
+        });
+        let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+        if !in_folder || file_name.starts_with('.') || file_name.starts_with('~') {
+            return Ok(None);
+        }
+
+        let extension = path.extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        if !self.config.file_extensions.contains(&extension) {
+            return Ok(None);
+        }
+
+        let metadata = match tokio::fs::metadata(path).await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(anyhow!("Failed to get metadata for {}: {}", path.display(), e)),
+        };
+
+        Ok(Some(Self::file_info(path, &metadata, &extension)))
+    }
+
+    /// The ingestion info of a file in one of the watched folders
+    fn file_info(path: &Path, metadata: &fs::Metadata, extension: &str) -> FileIngestionInfo {
+        let modified_time = metadata.modified()
+            .ok()
+            .and_then(|time| {
+                let duration = time.duration_since(std::time::UNIX_EPOCH).ok()?;
+                DateTime::from_timestamp(duration.as_secs() as i64, 0)
+            });
+
+        // Try to get creation time (not available on all systems)
+        let created_time = metadata.created()
+            .ok()
+            .and_then(|time| {
+                let duration = time.duration_since(std::time::UNIX_EPOCH).ok()?;
+                DateTime::from_timestamp(duration.as_secs() as i64, 0)
+            });
+
+        let file_name = path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        // Generate a simple hash-based ETag from file path and modification time
+        let etag = Self::generate_etag(path, metadata);
+
+        // Determine MIME type based on extension
+        let mime_type = Self::get_mime_type(extension);
+
+        // Extract file permissions and ownership info
+        #[cfg(unix)]
+        let (permissions, owner, group) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                Some(metadata.mode() & 0o777), // File mode bits (permissions)
+                Some(metadata.uid().to_string()), // User ID
+                Some(metadata.gid().to_string()), // Group ID
+            )
+        };
+        
+        #[cfg(not(unix))]
+        let (permissions, owner, group) = (None, None, None);
+
+        // Prepare additional metadata
+        let mut additional_metadata = serde_json::Map::new();
+        
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            additional_metadata.insert("inode".to_string(), serde_json::Value::Number(metadata.ino().into()));
+            additional_metadata.insert("nlinks".to_string(), serde_json::Value::Number(metadata.nlink().into()));
+            additional_metadata.insert("device".to_string(), serde_json::Value::Number(metadata.dev().into()));
+        }
+        
+        // Add file attributes
+        additional_metadata.insert("readonly".to_string(), serde_json::Value::Bool(metadata.permissions().readonly()));
+        
+        FileIngestionInfo {
+            relative_path: path.to_string_lossy().to_string(),
+            full_path: path.to_string_lossy().to_string(), // For filesystem, relative and full are the same
+            #[allow(deprecated)]
+            path: path.to_string_lossy().to_string(),
+            name: file_name,
+            size: metadata.len() as i64,
+            mime_type,
+            last_modified: modified_time,
+            etag,
+            is_directory: false,
+            created_at: created_time,
+            permissions,
+            owner,
+            group,
+            metadata: if additional_metadata.is_empty() { None } else { Some(serde_json::Value::Object(additional_metadata)) },
+        }
+    }
+
     /// Read file content for processing
     pub async fn read_file(&self, file_path: &str) -> Result<Vec<u8>> {
         let file_path = file_path.to_string();
@@ -284,6 +319,7 @@ mod tests {
             sync_interval_minutes: 60,
             recursive: false,
             follow_symlinks: false,
+            watch_mode: Default::default(),
         };
 
         let service = LocalFolderService::new(config).unwrap();
@@ -317,6 +353,7 @@ mod tests {
             sync_interval_minutes: 60,
             recursive: false,
             follow_symlinks: false,
+            watch_mode: Default::default(),
         };
 
         let service = LocalFolderService::new(config).unwrap();
@@ -324,4 +361,33 @@ mod tests {
         
         assert_eq!(content, test_content);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_watched_file_info() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        for name in ["scan.pdf", "notes.bin", ".scan.pdf", "nested/deep.pdf"] {
+            File::create(temp_dir.path().join(name)).unwrap().write_all(b"content").unwrap();
+        }
+
+        let config = LocalFolderSourceConfig {
+            watch_folders: vec![temp_dir.path().to_str().unwrap().to_string()],
+            file_extensions: vec!["pdf".to_string()],
+            auto_sync: true,
+            sync_interval_minutes: 60,
+            recursive: false,
+            follow_symlinks: false,
+            watch_mode: Default::default(),
+        };
+        let service = LocalFolderService::new(config).unwrap();
+
+        let info = service.watched_file_info(&temp_dir.path().join("scan.pdf")).await.unwrap().unwrap();
+        assert_eq!(info.name, "scan.pdf");
+        assert_eq!(info.size, 7);
+        assert!(service.watched_file_info(&temp_dir.path().join("notes.bin")).await.unwrap().is_none());
+        assert!(service.watched_file_info(&temp_dir.path().join(".scan.pdf")).await.unwrap().is_none());
+        assert!(service.watched_file_info(&temp_dir.path().join("gone.pdf")).await.unwrap().is_none());
+        // Subfolders are only synced when recursive
+        assert!(service.watched_file_info(&temp_dir.path().join("nested/deep.pdf")).await.unwrap().is_none());
+    }
+}
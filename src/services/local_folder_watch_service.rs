@@ -0,0 +1,342 @@
+use anyhow::{anyhow, Result};
+use notify::{Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    ingestion::document_ingestion::{DeduplicationPolicy, DocumentIngestionService, IngestionResult},
+    models::{LocalFolderSourceConfig, LocalFolderWatchMode, Source, SourceType},
+    services::local_folder_service::LocalFolderService,
+    AppState,
+};
+
+/// Filesystem types whose changes made on other machines never raise local notifications
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "9p", "afs", "ceph", "glusterfs", "davfs", "fuse.sshfs",
+    "fuse.rclone", "fuse.s3fs", "fuse.glusterfs",
+];
+
+/// How long a file must go without events before its size is checked
+fn debounce() -> Duration {
+    Duration::from_secs(env_seconds("LOCAL_FOLDER_WATCH_DEBOUNCE_SECONDS", 2))
+}
+
+fn poll_interval() -> Duration {
+    Duration::from_secs(env_seconds("LOCAL_FOLDER_POLL_INTERVAL_SECONDS", 30))
+}
+
+fn env_seconds(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(default)
+}
+
+/// Size and modification time of a file, compared to tell whether it is still being written
+type FileSnapshot = (u64, SystemTime);
+
+struct PendingFile {
+    last_event: Instant,
+    snapshot: Option<FileSnapshot>,
+    checked_at: Option<Instant>,
+}
+
+/// Files that changed and are waiting to be fully written. A file is ready once no event
+/// arrived for the debounce time and its size and modification time did not change
+/// between two checks a debounce time apart.
+struct PendingFiles {
+    debounce: Duration,
+    files: HashMap<PathBuf, PendingFile>,
+}
+
+impl PendingFiles {
+    fn new(debounce: Duration) -> Self {
+        Self { debounce, files: HashMap::new() }
+    }
+
+    fn touch(&mut self, path: PathBuf, now: Instant) {
+        self.files.insert(path, PendingFile { last_event: now, snapshot: None, checked_at: None });
+    }
+
+    /// Take the files that are ready. Files that disappeared are dropped.
+    fn take_ready(&mut self, now: Instant, observe: impl Fn(&Path) -> Option<FileSnapshot>) -> Vec<PathBuf> {
+        let debounce = self.debounce;
+        let mut ready = Vec::new();
+        self.files.retain(|path, pending| {
+            if now.duration_since(pending.last_event) < debounce {
+                return true;
+            }
+            if pending.checked_at.is_some_and(|checked| now.duration_since(checked) < debounce) {
+                return true;
+            }
+            let Some(snapshot) = observe(path) else {
+                return false;
+            };
+            if pending.snapshot == Some(snapshot) && snapshot.0 > 0 {
+                ready.push(path.clone());
+                return false;
+            }
+            pending.snapshot = Some(snapshot);
+            pending.checked_at = Some(now);
+            true
+        });
+        ready
+    }
+}
+
+fn snapshot(path: &Path) -> Option<FileSnapshot> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// The type of the filesystem a path is on, from the longest matching mount point in
+/// `/proc/self/mounts`
+fn filesystem_type<'a>(mounts: &'a str, path: &Path) -> Option<&'a str> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            path.starts_with(&mount_point).then_some((mount_point.len(), fs_type))
+        })
+        .max_by_key(|(length, _)| *length)
+        .map(|(_, fs_type)| fs_type)
+}
+
+/// Whether a folder needs polling: on a network mount, or when `FORCE_POLLING_WATCH` is set
+fn needs_polling(folder: &Path) -> bool {
+    if std::env::var("FORCE_POLLING_WATCH").is_ok() {
+        return true;
+    }
+    let Ok(canonical) = folder.canonicalize() else {
+        return true;
+    };
+    let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") else {
+        return false;
+    };
+    filesystem_type(&mounts, &canonical).is_some_and(|fs_type| NETWORK_FILESYSTEMS.contains(&fs_type))
+}
+
+/// Ingests files within seconds of their arrival in the folders of local folder sources,
+/// using filesystem notifications where the folder is local and polling where it is on a
+/// network mount. Scheduled syncs keep running and pick up anything a watcher missed.
+#[derive(Clone)]
+pub struct LocalFolderWatchService {
+    state: Arc<AppState>,
+}
+
+impl LocalFolderWatchService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Keep one watcher running for every enabled local folder source with auto sync,
+    /// picking up added, changed and removed sources every minute
+    pub async fn run(self) {
+        let mut watchers: HashMap<Uuid, (serde_json::Value, CancellationToken)> = HashMap::new();
+        loop {
+            match self.state.db.get_all_sources().await {
+                Ok(sources) => {
+                    let wanted: HashMap<Uuid, Source> = sources
+                        .into_iter()
+                        .filter(|s| s.enabled && s.source_type == SourceType::LocalFolder)
+                        .filter(|s| {
+                            serde_json::from_value::<LocalFolderSourceConfig>(s.config.clone())
+                                .is_ok_and(|config| config.auto_sync && config.watch_mode != LocalFolderWatchMode::Off)
+                        })
+                        .map(|s| (s.id, s))
+                        .collect();
+
+                    watchers.retain(|source_id, (config, token)| {
+                        let keep = wanted.get(source_id).is_some_and(|source| source.config == *config);
+                        if !keep {
+                            token.cancel();
+                        }
+                        keep
+                    });
+
+                    for (source_id, source) in wanted {
+                        if watchers.contains_key(&source_id) {
+                            continue;
+                        }
+                        let token = CancellationToken::new();
+                        let service = self.clone();
+                        let watcher_token = token.clone();
+                        let config = source.config.clone();
+                        crate::errors::panic::spawn_guarded(format!("Local folder watcher {}", source_id), async move {
+                            if let Err(e) = service.watch(source, watcher_token).await {
+                                error!("Local folder watcher for source {} stopped: {}", source_id, e);
+                            }
+                        });
+                        watchers.insert(source_id, (config, token));
+                    }
+                }
+                Err(e) => error!("Failed to load sources for local folder watchers: {}", e),
+            }
+
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        }
+    }
+
+    async fn watch(&self, source: Source, token: CancellationToken) -> Result<()> {
+        let config: LocalFolderSourceConfig = serde_json::from_value(source.config.clone())
+            .map_err(|e| anyhow!("Invalid LocalFolder config: {}", e))?;
+        let local_service = LocalFolderService::new(config.clone())?;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<PathBuf>();
+        let handler = move |result: notify::Result<Event>| match result {
+            Ok(event) => {
+                let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Any);
+                if relevant {
+                    for path in event.paths {
+                        let _ = tx.send(path);
+                    }
+                }
+            }
+            Err(e) => warn!("Local folder watch error: {}", e),
+        };
+
+        let recursive_mode = if config.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        let mut watchers: Vec<Box<dyn Watcher + Send>> = Vec::new();
+        for folder in &config.watch_folders {
+            let folder = Path::new(folder);
+            let polling = match config.watch_mode {
+                LocalFolderWatchMode::Polling => true,
+                LocalFolderWatchMode::Notify => false,
+                _ => needs_polling(folder),
+            };
+
+            if !polling {
+                let notify_watcher = RecommendedWatcher::new(handler.clone(), notify::Config::default())
+                    .and_then(|mut watcher| watcher.watch(folder, recursive_mode).map(|_| watcher));
+                match notify_watcher {
+                    Ok(watcher) => {
+                        info!("Watching {} of source '{}' with filesystem notifications", folder.display(), source.name);
+                        watchers.push(Box::new(watcher));
+                        continue;
+                    }
+                    Err(e) => warn!(
+                        "Filesystem notifications unavailable for {} of source '{}', polling instead: {}",
+                        folder.display(),
+                        source.name,
+                        e
+                    ),
+                }
+            }
+
+            let mut watcher = PollWatcher::new(handler.clone(), notify::Config::default().with_poll_interval(poll_interval()))?;
+            watcher.watch(folder, recursive_mode)?;
+            info!(
+                "Polling {} of source '{}' every {} seconds",
+                folder.display(),
+                source.name,
+                poll_interval().as_secs()
+            );
+            watchers.push(Box::new(watcher));
+        }
+
+        let mut pending = PendingFiles::new(debounce());
+        let mut tick = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                Some(path) = rx.recv() => pending.touch(path, Instant::now()),
+                _ = tick.tick() => {
+                    for path in pending.take_ready(Instant::now(), snapshot) {
+                        if let Err(e) = self.ingest_file(&source, &local_service, &path).await {
+                            error!("Failed to ingest {} for source '{}': {}", path.display(), source.name, e);
+                        }
+                    }
+                }
+            }
+        }
+
+        drop(watchers);
+        debug!("Local folder watcher for source {} stopped", source.id);
+        Ok(())
+    }
+
+    async fn ingest_file(&self, source: &Source, local_service: &LocalFolderService, path: &Path) -> Result<()> {
+        let Some(file_info) = local_service.watched_file_info(path).await? else {
+            return Ok(());
+        };
+        let data = local_service.read_file(&file_info.relative_path).await?;
+
+        let ingestion_service =
+            DocumentIngestionService::new(self.state.db.clone(), (*self.state.file_service).clone());
+        let result = ingestion_service
+            .ingest_from_file_info(&file_info, data, source.user_id, DeduplicationPolicy::Skip, "local_folder_watch", Some(source.id))
+            .await
+            .map_err(|e| anyhow!("Document ingestion failed for {}: {}", file_info.name, e))?;
+
+        match result {
+            IngestionResult::Created(document) | IngestionResult::NewVersion(document) => {
+                info!("Ingested {} from local folder source '{}' as document {}", file_info.name, source.name, document.id);
+                let priority = if file_info.size <= 1024 * 1024 { 10 } else if file_info.size <= 10 * 1024 * 1024 { 6 } else { 2 };
+                if let Err(e) = self.state.queue_service.enqueue_document(document.id, priority, file_info.size).await {
+                    error!("Failed to enqueue document {} for OCR: {}", document.id, e);
+                }
+            }
+            IngestionResult::ExistingDocument(_)
+            | IngestionResult::Skipped { .. }
+            | IngestionResult::TrackedAsDuplicate { .. } => {
+                debug!("{} from local folder source '{}' is already ingested", file_info.name, source.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_are_ready_once_quiet_and_stable() {
+        let debounce = Duration::from_secs(2);
+        let mut pending = PendingFiles::new(debounce);
+        let start = Instant::now();
+        let path = PathBuf::from("/inbox/scan.pdf");
+        let modified = SystemTime::UNIX_EPOCH;
+        pending.touch(path.clone(), start);
+
+        // Still receiving events
+        assert!(pending.take_ready(start + Duration::from_secs(1), |_| Some((10, modified))).is_empty());
+        // Quiet, first size check
+        assert!(pending.take_ready(start + debounce, |_| Some((10, modified))).is_empty());
+        // Grew since the first check
+        assert!(pending.take_ready(start + debounce * 2, |_| Some((20, modified))).is_empty());
+        // Unchanged since the last check
+        assert_eq!(pending.take_ready(start + debounce * 3, |_| Some((20, modified))), vec![path]);
+        assert!(pending.files.is_empty());
+    }
+
+    #[test]
+    fn test_vanished_files_are_dropped() {
+        let mut pending = PendingFiles::new(Duration::from_secs(2));
+        let start = Instant::now();
+        pending.touch(PathBuf::from("/inbox/scan.pdf.part"), start);
+
+        assert!(pending.take_ready(start + Duration::from_secs(5), |_| None).is_empty());
+        assert!(pending.files.is_empty());
+    }
+
+    #[test]
+    fn test_filesystem_type_uses_longest_mount_point() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n\
+                      nas:/scans /mnt/scanner\\040inbox nfs4 rw 0 0\n\
+                      tmpfs /mnt tmpfs rw 0 0\n";
+
+        assert_eq!(filesystem_type(mounts, Path::new("/mnt/scanner inbox/2024")), Some("nfs4"));
+        assert_eq!(filesystem_type(mounts, Path::new("/mnt/other")), Some("tmpfs"));
+        assert_eq!(filesystem_type(mounts, Path::new("/srv/documents")), Some("ext4"));
+    }
+}
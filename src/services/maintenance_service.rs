@@ -0,0 +1,136 @@
+//! Paused queues and the maintenance mode. Admins change them on any node; every node
+//! keeps a copy it refreshes every few seconds, which the queue workers check before
+//! taking work and the maintenance middleware checks on every request, so neither
+//! asks the database each time.
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::db::Database;
+use crate::models::{OperationControls, QueueKind};
+
+/// How often each node reloads the controls another node may have changed
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// Seconds clients are told to wait before writing again
+const RETRY_AFTER_SECONDS: u64 = 60;
+
+static CONTROLS: Lazy<RwLock<OperationControls>> = Lazy::new(|| RwLock::new(OperationControls::default()));
+
+/// The controls as this node last saw them
+pub fn current() -> OperationControls {
+    CONTROLS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Whether a queue takes no work, because it is paused or for maintenance
+pub fn holds(queue: QueueKind) -> bool {
+    CONTROLS.read().unwrap_or_else(|e| e.into_inner()).holds(queue)
+}
+
+/// Use controls just saved on this node, before the next refresh
+pub fn set(controls: OperationControls) {
+    let mut current = CONTROLS.write().unwrap_or_else(|e| e.into_inner());
+    if current.maintenance_mode != controls.maintenance_mode {
+        if controls.maintenance_mode {
+            info!("Maintenance mode is on, rejecting writes");
+        } else {
+            info!("Maintenance mode is off");
+        }
+    }
+    *current = controls;
+}
+
+pub async fn refresh(db: &Database) {
+    match db.get_operation_controls().await {
+        Ok(controls) => set(controls),
+        Err(e) => warn!("Failed to load the queue and maintenance controls: {}", e),
+    }
+}
+
+/// Keep this node's copy of the controls current; runs on every node
+pub async fn run_refresh(db: Database) {
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        refresh(&db).await;
+    }
+}
+
+/// Whether a request changes anything. The GraphQL schema has no mutations and
+/// PROPFIND only lists the WebDAV library.
+pub fn is_write(method: &Method, path: &str) -> bool {
+    let reads = method == Method::GET
+        || method == Method::HEAD
+        || method == Method::OPTIONS
+        || method.as_str() == "PROPFIND"
+        || (method == Method::POST && path.trim_end_matches('/') == "/api/graphql");
+    !reads
+}
+
+/// Writes still taken in maintenance mode: signing in and out, and the controls
+/// themselves, so the admin can end it
+pub fn allowed_in_maintenance(path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    path.starts_with("/api/auth/")
+        || path == "/api/maintenance"
+        || (path.starts_with("/api/queue/") && (path.ends_with("/pause") || path.ends_with("/resume")))
+}
+
+/// Rejects writes with 503 while the maintenance mode is on
+pub async fn maintenance_middleware(request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if !is_write(request.method(), path) || allowed_in_maintenance(path) {
+        return next.run(request).await;
+    }
+    let controls = current();
+    if !controls.maintenance_mode {
+        return next.run(request).await;
+    }
+
+    let mut response = (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "error": "maintenance_mode",
+            "message": controls.message(),
+            "since": controls.maintenance_started_at,
+        })),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECONDS));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writes() {
+        assert!(!is_write(&Method::GET, "/api/documents"));
+        assert!(!is_write(&Method::POST, "/api/graphql"));
+        assert!(!is_write(&Method::from_bytes(b"PROPFIND").unwrap(), "/dav/Labels"));
+        assert!(is_write(&Method::POST, "/api/documents"));
+        assert!(is_write(&Method::DELETE, "/api/documents/123"));
+    }
+
+    #[test]
+    fn test_allowed_in_maintenance() {
+        assert!(allowed_in_maintenance("/api/auth/login"));
+        assert!(allowed_in_maintenance("/api/maintenance"));
+        assert!(allowed_in_maintenance("/api/queue/sync/resume"));
+        assert!(!allowed_in_maintenance("/api/queue/requeue-failed"));
+        assert!(!allowed_in_maintenance("/api/documents"));
+    }
+}
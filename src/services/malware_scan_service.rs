@@ -0,0 +1,338 @@
+//! Malware scanning of incoming files with ClamAV's clamd, before they are stored.
+//!
+//! Files are streamed to clamd with its `INSTREAM` command. Infected files are moved
+//! to the quarantine directory and recorded in `quarantined_files` instead of
+//! becoming documents; only admins can see them there.
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::QuarantinedFile;
+use crate::services::file_service::FileService;
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 60;
+/// Size of the chunks a file is sent to clamd in
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Longest reply read from clamd
+const MAX_REPLY_LENGTH: u64 = 4096;
+/// Directory of the upload path infected files are kept in
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+static SCANNER: Lazy<Result<Option<MalwareScanner>, String>> =
+    Lazy::new(|| MalwareScanner::from_env().map_err(|e| e.to_string()));
+
+/// The scanner configured in the environment: None when scanning is off, an error
+/// when `CLAMAV_ADDRESS` is set but unusable
+pub fn configured() -> Result<Option<MalwareScanner>, String> {
+    SCANNER.clone()
+}
+
+/// Where clamd listens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClamdAddress {
+    Unix(PathBuf),
+    /// `host:port`
+    Tcp(String),
+}
+
+impl ClamdAddress {
+    /// `unix:///run/clamav/clamd.ctl`, `/run/clamav/clamd.ctl`, `tcp://clamav:3310` or `clamav:3310`
+    pub fn parse(address: &str) -> Result<Self> {
+        let address = address.trim();
+        if let Some(path) = address.strip_prefix("unix://").or_else(|| address.strip_prefix("unix:")) {
+            return Ok(ClamdAddress::Unix(PathBuf::from(path)));
+        }
+        if address.starts_with('/') {
+            return Ok(ClamdAddress::Unix(PathBuf::from(address)));
+        }
+
+        let host_port = address.strip_prefix("tcp://").unwrap_or(address);
+        match host_port.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+                Ok(ClamdAddress::Tcp(host_port.to_string()))
+            }
+            _ => Err(anyhow!("Invalid clamd address '{}': expected a socket path or host:port", address)),
+        }
+    }
+}
+
+impl std::fmt::Display for ClamdAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClamdAddress::Unix(path) => write!(f, "unix://{}", path.display()),
+            ClamdAddress::Tcp(host_port) => write!(f, "tcp://{}", host_port),
+        }
+    }
+}
+
+/// What clamd found in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// Infected, with the signature that matched
+    Infected(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct MalwareScanner {
+    address: ClamdAddress,
+    timeout: Duration,
+    /// Ingest files unscanned when clamd cannot be reached, instead of rejecting them
+    fail_open: bool,
+}
+
+impl MalwareScanner {
+    pub fn new(address: ClamdAddress, timeout: Duration, fail_open: bool) -> Self {
+        Self { address, timeout, fail_open }
+    }
+
+    /// From `CLAMAV_ADDRESS`, `CLAMAV_TIMEOUT_SECONDS` and `CLAMAV_FAIL_OPEN`
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(address) = std::env::var("CLAMAV_ADDRESS").ok().filter(|v| !v.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let address = ClamdAddress::parse(&address)?;
+        let timeout = std::env::var("CLAMAV_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_SECONDS)
+            .max(1);
+        let fail_open = std::env::var("CLAMAV_FAIL_OPEN")
+            .map(|v| matches!(v.to_lowercase().as_str(), "true" | "1" | "yes"))
+            .unwrap_or(false);
+
+        Ok(Some(Self::new(address, Duration::from_secs(timeout), fail_open)))
+    }
+
+    pub fn fail_open(&self) -> bool {
+        self.fail_open
+    }
+
+    pub fn describe(&self) -> String {
+        let on_failure = if self.fail_open { "accepted unscanned" } else { "rejected" };
+        format!("clamd at {}, files {} when it is unavailable", self.address, on_failure)
+    }
+
+    /// Check that clamd answers
+    pub async fn ping(&self) -> Result<()> {
+        let reply = tokio::time::timeout(self.timeout, async {
+            match &self.address {
+                ClamdAddress::Tcp(host_port) => command(tokio::net::TcpStream::connect(host_port).await?, b"zPING\0").await,
+                #[cfg(unix)]
+                ClamdAddress::Unix(path) => command(tokio::net::UnixStream::connect(path).await?, b"zPING\0").await,
+                #[cfg(not(unix))]
+                ClamdAddress::Unix(_) => Err(anyhow!("Unix sockets are not supported on this platform")),
+            }
+        })
+        .await
+        .map_err(|_| anyhow!("clamd did not answer within {:?}", self.timeout))??;
+
+        if reply == "PONG" {
+            Ok(())
+        } else {
+            Err(anyhow!("Unexpected reply from clamd: {}", reply))
+        }
+    }
+
+    /// Stream `data` to clamd and return its verdict
+    pub async fn scan(&self, data: &[u8]) -> Result<ScanVerdict> {
+        let reply = tokio::time::timeout(self.timeout, async {
+            match &self.address {
+                ClamdAddress::Tcp(host_port) => instream(tokio::net::TcpStream::connect(host_port).await?, data).await,
+                #[cfg(unix)]
+                ClamdAddress::Unix(path) => instream(tokio::net::UnixStream::connect(path).await?, data).await,
+                #[cfg(not(unix))]
+                ClamdAddress::Unix(_) => Err(anyhow!("Unix sockets are not supported on this platform")),
+            }
+        })
+        .await
+        .map_err(|_| anyhow!("clamd did not answer within {:?}", self.timeout))??;
+
+        parse_reply(&reply)
+    }
+}
+
+/// Send a command without payload and read the reply
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, command: &[u8]) -> Result<String> {
+    stream.write_all(command).await?;
+    read_reply(stream).await
+}
+
+/// Send `data` in length-prefixed chunks, ended by an empty chunk
+async fn instream<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, data: &[u8]) -> Result<String> {
+    let sent = async {
+        stream.write_all(b"zINSTREAM\0").await?;
+        for chunk in data.chunks(CHUNK_SIZE) {
+            stream.write_all(&(chunk.len() as u32).to_be_bytes()).await?;
+            stream.write_all(chunk).await?;
+        }
+        stream.write_all(&0u32.to_be_bytes()).await?;
+        stream.flush().await
+    }
+    .await;
+
+    // clamd closes the connection early when the file is over its StreamMaxLength,
+    // after explaining why
+    match (sent, read_reply(stream).await) {
+        (_, Ok(reply)) if !reply.is_empty() => Ok(reply),
+        (Err(e), _) => Err(anyhow!("Failed to send the file to clamd: {}", e)),
+        (Ok(()), Err(e)) => Err(e),
+        (Ok(()), Ok(_)) => Err(anyhow!("clamd closed the connection without a verdict")),
+    }
+}
+
+async fn read_reply<S: AsyncRead + Unpin>(stream: S) -> Result<String> {
+    let mut reply = Vec::new();
+    stream.take(MAX_REPLY_LENGTH).read_to_end(&mut reply).await?;
+    Ok(String::from_utf8_lossy(&reply).trim_end_matches('\0').trim().to_string())
+}
+
+/// `stream: OK`, `stream: Eicar-Signature FOUND` or `... ERROR`
+pub fn parse_reply(reply: &str) -> Result<ScanVerdict> {
+    let result = reply.strip_prefix("stream:").unwrap_or(reply).trim();
+    if result == "OK" {
+        return Ok(ScanVerdict::Clean);
+    }
+    if let Some(signature) = result.strip_suffix("FOUND") {
+        return Ok(ScanVerdict::Infected(signature.trim().to_string()));
+    }
+    Err(anyhow!("clamd could not scan the file: {}", result))
+}
+
+/// The scan result recorded in a document's metadata under `malware_scan`
+pub fn scan_metadata(status: &str, error: Option<&str>) -> serde_json::Value {
+    let mut result = serde_json::json!({
+        "scanner": "clamav",
+        "status": status,
+        "scanned_at": Utc::now(),
+    });
+    if let Some(error) = error {
+        result["error"] = serde_json::Value::String(error.to_string());
+    }
+    result
+}
+
+/// Ingestion was refused because the file is infected; it was quarantined instead
+#[derive(Debug)]
+pub struct MalwareDetected {
+    pub filename: String,
+    pub signature: String,
+    pub quarantine_id: Uuid,
+}
+
+impl std::fmt::Display for MalwareDetected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Malware detected in {} ({}); it was quarantined", self.filename, self.signature)
+    }
+}
+
+impl std::error::Error for MalwareDetected {}
+
+/// Keep an infected file out of the library: store it in the quarantine directory,
+/// where no document points to it, and record it for admins
+pub async fn quarantine(db: &Database, file_service: &FileService, file: QuarantinedFile, data: &[u8]) -> Result<QuarantinedFile> {
+    let directory = file_service.get_subdirectory_path(QUARANTINE_DIR);
+    tokio::fs::create_dir_all(&directory).await?;
+
+    // Named by id only, so the file cannot be opened by its extension
+    let file_path = directory.join(file.id.to_string());
+    tokio::fs::write(&file_path, data).await?;
+
+    let file = QuarantinedFile { file_path: file_path.to_string_lossy().to_string(), ..file };
+    match db.create_quarantined_file(&file).await {
+        Ok(quarantined) => {
+            warn!(
+                "Quarantined {} of user {} as {}: {}",
+                quarantined.original_filename, quarantined.user_id, quarantined.id, quarantined.signature
+            );
+            Ok(quarantined)
+        }
+        Err(e) => {
+            if let Err(remove_err) = tokio::fs::remove_file(&file_path).await {
+                warn!("Failed to remove unrecorded quarantined file {}: {}", file_path.display(), remove_err);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Delete a quarantined file and its record. False when there is no such file.
+pub async fn delete_quarantined(db: &Database, id: Uuid) -> Result<bool> {
+    let Some(file) = db.delete_quarantined_file(id).await? else {
+        return Ok(false);
+    };
+    match tokio::fs::remove_file(&file.file_path).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to remove quarantined file {}: {}", file.file_path, e),
+    }
+    info!("Deleted quarantined file {} ({})", file.id, file.original_filename);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(
+            ClamdAddress::parse("unix:///run/clamav/clamd.ctl").unwrap(),
+            ClamdAddress::Unix(PathBuf::from("/run/clamav/clamd.ctl"))
+        );
+        assert_eq!(
+            ClamdAddress::parse("/run/clamav/clamd.ctl").unwrap(),
+            ClamdAddress::Unix(PathBuf::from("/run/clamav/clamd.ctl"))
+        );
+        assert_eq!(ClamdAddress::parse("tcp://clamav:3310").unwrap(), ClamdAddress::Tcp("clamav:3310".to_string()));
+        assert_eq!(ClamdAddress::parse("127.0.0.1:3310").unwrap(), ClamdAddress::Tcp("127.0.0.1:3310".to_string()));
+        assert!(ClamdAddress::parse("clamav").is_err());
+        assert!(ClamdAddress::parse("clamav:port").is_err());
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(parse_reply("stream: OK").unwrap(), ScanVerdict::Clean);
+        assert_eq!(
+            parse_reply("stream: Win.Test.EICAR_HDB-1 FOUND").unwrap(),
+            ScanVerdict::Infected("Win.Test.EICAR_HDB-1".to_string())
+        );
+        assert!(parse_reply("INSTREAM size limit exceeded. ERROR").is_err());
+        assert!(parse_reply("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_instream_protocol() {
+        let (client, mut server) = tokio::io::duplex(1024 * 1024);
+        let data = vec![7u8; CHUNK_SIZE + 10];
+
+        let clamd = tokio::spawn(async move {
+            let mut request = Vec::new();
+            let mut buffer = vec![0u8; 8192];
+            // Command, two chunks and the terminating empty chunk
+            let expected = 10 + 4 + CHUNK_SIZE + 4 + 10 + 4;
+            while request.len() < expected {
+                let read = server.read(&mut buffer).await.unwrap();
+                assert!(read > 0);
+                request.extend_from_slice(&buffer[..read]);
+            }
+            server.write_all(b"stream: OK\0").await.unwrap();
+            request
+        });
+
+        let reply = instream(client, &data).await.unwrap();
+        assert_eq!(reply, "stream: OK");
+
+        let request = clamd.await.unwrap();
+        assert_eq!(&request[..10], b"zINSTREAM\0");
+        assert_eq!(&request[10..14], &(CHUNK_SIZE as u32).to_be_bytes());
+        assert_eq!(&request[request.len() - 4..], &0u32.to_be_bytes());
+    }
+}
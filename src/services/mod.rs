@@ -1,14 +1,101 @@
+pub mod annotation_service;
+pub mod audit_service;
+pub mod calendar_service;
+pub mod capture_service;
+pub mod cloud_drive;
+pub mod dropbox_service;
+pub mod event_service;
 pub mod file_service;
+pub mod filename_template;
+pub mod form_extraction_service;
+pub mod ftp_drop_service;
+pub mod imap_service;
 pub mod local_folder_service;
+pub mod local_folder_watch_service;
 pub mod local_folder_error_classifier;
+pub mod malware_scan_service;
 pub mod ocr_retry_service;
+pub mod ocr_regex_report;
+pub mod office_preview;
+pub mod oidc_provisioning_service;
+pub mod onedrive_service;
+pub mod page_artifact_service;
+pub mod passkey_service;
+pub mod pdf_security_service;
+pub mod pdf_signature_service;
+pub mod legal_hold_service;
+pub mod library_dav_service;
+pub mod search_backend;
+pub mod search_index_service;
+pub mod analysis_batch_service;
+pub mod ocr_comparison_service;
+pub mod ocr_word_service;
+pub mod entity_service;
+pub mod translation_service;
+pub mod narration_service;
+pub mod ingestion_hook_service;
+pub mod failed_document_service;
+pub mod document_progress;
+pub mod pii_service;
+pub mod processing_usage;
+pub mod i18n;
+pub mod title_service;
+pub mod rate_limit_service;
+pub mod outbound_limits;
+pub mod data_residency;
+pub mod redaction_service;
+pub mod reminder_service;
+pub mod digest_service;
+pub mod collection_export_service;
+pub mod region_ocr_service;
+pub mod omr_service;
+pub mod thumbnail_service;
+pub mod consistency_service;
+pub mod converter_sandbox;
+pub mod document_pages_service;
+pub mod page_transform_service;
+pub mod maintenance_service;
+pub mod expiration_service;
+pub mod document_split_service;
+pub mod document_version_service;
+pub mod document_compare_service;
+pub mod email_attachment_service;
+pub mod encryption;
+pub mod external_search;
+pub mod retention_service;
+pub mod saved_search_service;
+pub mod scan_bridge_service;
+pub mod correspondent_service;
+pub mod invoice_extraction_service;
+pub mod label_rule_service;
+pub mod bulk_operation_service;
+pub mod notification_service;
+pub mod export_service;
+pub mod import_service;
+pub mod backup_service;
+pub mod upload_service;
+pub mod share_link_service;
+pub mod s3_event_service;
+pub mod s3_integrity;
 pub mod s3_service;
 pub mod s3_service_stub;
 pub mod s3_error_classifier;
+pub mod sftp_service;
+pub mod similar_document_service;
+pub mod source_rule_simulation;
+pub mod source_sync_preview;
+pub mod storage_compression_service;
+pub mod storage_layout;
+pub mod storage_migration_service;
+pub mod storage_quota_service;
+pub mod storage_relocation_service;
 pub mod source_error_tracker;
 pub mod sync_progress_tracker;
+pub mod two_factor_service;
 pub mod user_watch_service;
+pub mod vision_fallback_service;
 pub mod llm;
 pub mod webdav;
 pub mod webdav_metrics_simple;
-pub mod webdav_metrics_integration;
\ No newline at end of file
+pub mod webdav_metrics_integration;
+pub mod workspace_service;
\ No newline at end of file
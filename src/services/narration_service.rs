@@ -0,0 +1,217 @@
+//! Spoken narrations of document text, made with an OpenAI-compatible text-to-speech
+//! API and stored with the document's files.
+
+use anyhow::{anyhow, Result};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{AudioStatus, ContentDestination, Document, DocumentAudio};
+use crate::ocr::PAGE_BREAK;
+use crate::services::data_residency;
+use crate::services::file_service::FileService;
+use crate::utils::text_chunks::split_text_chunks;
+
+/// `POST` endpoint of an OpenAI-compatible speech API, e.g. `https://api.openai.com/v1/audio/speech`
+static TTS_API_URL: Lazy<Option<String>> =
+    Lazy::new(|| std::env::var("TTS_API_URL").ok().map(|url| url.trim().to_string()).filter(|url| !url.is_empty()));
+
+static TTS_API_KEY: Lazy<Option<String>> =
+    Lazy::new(|| std::env::var("TTS_API_KEY").ok().filter(|key| !key.trim().is_empty()));
+
+static TTS_MODEL: Lazy<String> = Lazy::new(|| {
+    std::env::var("TTS_MODEL").ok().filter(|model| !model.trim().is_empty()).unwrap_or_else(|| "tts-1".to_string())
+});
+
+static TTS_VOICE: Lazy<String> = Lazy::new(|| {
+    std::env::var("TTS_VOICE").ok().filter(|voice| !voice.trim().is_empty()).unwrap_or_else(|| "alloy".to_string())
+});
+
+/// Longest document text that is narrated, in characters
+pub const MAX_NARRATION_CHARS: usize = 200_000;
+
+/// The audio is MP3, whose frames can be joined one request after another
+pub const NARRATION_MIME_TYPE: &str = "audio/mpeg";
+
+/// Name the audio is stored under, next to the narration's id
+const NARRATION_FILENAME: &str = "narration.mp3";
+
+/// Characters sent per request; OpenAI takes at most 4096
+const CHUNK_CHARS: usize = 4000;
+
+/// Requests in flight for one document
+const CHUNK_CONCURRENCY: usize = 3;
+
+const API_TIMEOUT_SECONDS: u64 = 300;
+
+/// Makes narrations in the background. A narration interrupted by a restart is
+/// made again from the start.
+pub struct NarrationService {
+    db: Database,
+    file_service: FileService,
+    client: Client,
+}
+
+impl NarrationService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(API_TIMEOUT_SECONDS))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        Self { db, file_service, client }
+    }
+
+    /// The text-to-speech endpoint, when one is configured
+    pub fn api_url() -> Option<&'static str> {
+        TTS_API_URL.as_deref()
+    }
+
+    /// Whether a text-to-speech API is configured
+    pub fn enabled() -> bool {
+        TTS_API_URL.is_some()
+    }
+
+    pub fn model() -> &'static str {
+        TTS_MODEL.as_str()
+    }
+
+    pub fn default_voice() -> &'static str {
+        TTS_VOICE.as_str()
+    }
+
+    /// The text a document is narrated from: its OCR text, or else its extracted text
+    pub fn document_text(document: &Document) -> Option<&str> {
+        let usable = |text: &&str| !text.trim().is_empty();
+        document
+            .ocr_text
+            .as_deref()
+            .filter(usable)
+            .or_else(|| document.content.as_deref().filter(usable))
+    }
+
+    /// Make the narrations that were queued or running when the server stopped
+    pub async fn resume_interrupted(&self) -> Result<()> {
+        for audio in self.db.get_unfinished_document_audio().await? {
+            info!("Restarting narration of document {}", audio.document_id);
+            if let Err(e) = self.run(audio.document_id).await {
+                warn!("Narration of document {} stopped: {}", audio.document_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Speak the document's text and store the audio. Returns None when the
+    /// narration was removed before it was done.
+    pub async fn run(&self, document_id: Uuid) -> Result<Option<DocumentAudio>> {
+        let audio = self
+            .db
+            .get_document_audio(document_id)
+            .await?
+            .ok_or_else(|| anyhow!("Narration of document {} not found", document_id))?;
+        if !audio.is_pending() {
+            return Ok(Some(audio));
+        }
+        let document = self
+            .db
+            .get_documents_by_ids(&[document_id])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("Document {} not found", document_id))?;
+
+        self.db.start_document_audio(document_id).await?;
+        let speech = match Self::document_text(&document) {
+            Some(text) => self.synthesize(document_id, text, &audio.voice).await,
+            None => Err("The document has no text".to_string()),
+        };
+        let data = match speech {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Narration of document {} failed: {}", document_id, e);
+                return self
+                    .db
+                    .finish_document_audio(document_id, AudioStatus::Failed, None, None, Some(&e))
+                    .await;
+            }
+        };
+
+        let file_path = self
+            .file_service
+            .save_document_file(document.user_id, audio.id, NARRATION_FILENAME, &data)
+            .await?;
+        let finished = self
+            .db
+            .finish_document_audio(document_id, AudioStatus::Completed, Some(&file_path), Some(data.len() as i64), None)
+            .await?;
+        match &finished {
+            Some(_) => info!("Narrated document {} ({} bytes)", document_id, data.len()),
+            // Removed while it was being spoken
+            None => self.delete_file(document.user_id, &audio, &file_path).await,
+        }
+        Ok(finished)
+    }
+
+    /// Remove the stored audio of a narration, if it has one
+    pub async fn delete_audio_file(&self, owner_id: Uuid, audio: &DocumentAudio) {
+        if let Some(file_path) = &audio.file_path {
+            self.delete_file(owner_id, audio, file_path).await;
+        }
+    }
+
+    async fn delete_file(&self, owner_id: Uuid, audio: &DocumentAudio, file_path: &str) {
+        if let Err(e) = self
+            .file_service
+            .delete_saved_file(owner_id, audio.id, NARRATION_FILENAME, file_path)
+            .await
+        {
+            warn!("Failed to delete narration of document {}: {}", audio.document_id, e);
+        }
+    }
+
+    /// Speak the text piece by piece and join the audio in order
+    async fn synthesize(&self, document_id: Uuid, text: &str, voice: &str) -> Result<Vec<u8>, String> {
+        let url = TTS_API_URL.as_deref().ok_or("Text-to-speech API is not configured")?;
+        data_residency::authorize(self.db.get_pool(), ContentDestination::TextToSpeech, url, &[document_id], text.len())
+            .await?;
+
+        let text = text.replace(PAGE_BREAK, "\n\n");
+        // Owned chunks keep the stream free of borrows of `text`, so the narration task
+        // stays `Send` when spawned
+        let chunks: Vec<String> = split_text_chunks(&text, CHUNK_CHARS).into_iter().map(str::to_string).collect();
+        let parts: Vec<Vec<u8>> = stream::iter(chunks)
+            .map(|chunk| async move { self.request_speech(&chunk, voice).await })
+            .buffered(CHUNK_CONCURRENCY)
+            .try_collect()
+            .await?;
+        Ok(parts.concat())
+    }
+
+    async fn request_speech(&self, chunk: &str, voice: &str) -> Result<Vec<u8>, String> {
+        let url = TTS_API_URL.as_deref().ok_or("Text-to-speech API is not configured")?;
+        let mut request = self.client.post(url).json(&serde_json::json!({
+            "model": TTS_MODEL.as_str(),
+            "input": chunk,
+            "voice": voice,
+            "response_format": "mp3",
+        }));
+        if let Some(key) = TTS_API_KEY.as_deref() {
+            request = request.bearer_auth(key);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach text-to-speech API: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Text-to-speech API returned error: {}", response.status()));
+        }
+        let audio = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read text-to-speech API response: {}", e))?;
+        Ok(audio.to_vec())
+    }
+}
@@ -0,0 +1,319 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use reqwest::Client;
+use serde_json::json;
+use std::collections::{hash_map::Entry, HashMap};
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{Notification, NotificationChannel, NotificationChannelConfig};
+use crate::services::i18n;
+
+const DEFAULT_NTFY_SERVER: &str = "https://ntfy.sh";
+/// How often new notifications are looked for
+const DISPATCH_POLL_SECONDS: u64 = 10;
+const DISPATCH_BATCH_SIZE: i64 = 100;
+const SEND_TIMEOUT_SECONDS: u64 = 10;
+
+/// Server-wide SMTP settings email channels are sent with
+#[derive(Debug, Clone)]
+struct SmtpSettings {
+    host: String,
+    port: u16,
+    username: Option<String>,
+    password: Option<String>,
+    from: String,
+    use_tls: bool,
+}
+
+impl SmtpSettings {
+    /// None unless `SMTP_HOST` and `SMTP_FROM_ADDRESS` are set
+    fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        Some(Self {
+            host: var("SMTP_HOST")?,
+            port: var("SMTP_PORT").and_then(|v| v.parse().ok()).unwrap_or(587),
+            username: var("SMTP_USERNAME"),
+            password: var("SMTP_PASSWORD"),
+            from: var("SMTP_FROM_ADDRESS")?,
+            use_tls: var("SMTP_USE_TLS").is_none_or(|v| v.eq_ignore_ascii_case("true") || v == "1"),
+        })
+    }
+}
+
+/// ntfy priority (1-5) of a notification type
+fn ntfy_priority(notification_type: &str) -> u8 {
+    match notification_type {
+        "error" | "warning" => 4,
+        _ => 3,
+    }
+}
+
+/// Gotify priority (0-10) of a notification type
+fn gotify_priority(notification_type: &str) -> u8 {
+    match notification_type {
+        "error" => 8,
+        "warning" => 5,
+        _ => 2,
+    }
+}
+
+/// Sends in-app notifications on to the email, ntfy, Gotify and Slack channels of their
+/// user that opted into their type. Notifications are picked up after they are stored,
+/// so none of the code creating them has to know about channels; a failed send is
+/// recorded on the channel and not retried.
+#[derive(Clone)]
+pub struct NotificationService {
+    db: Database,
+    client: Client,
+    smtp: Option<SmtpSettings>,
+    /// Base URL of the web interface, for links to what a notification is about
+    public_url: Option<String>,
+}
+
+impl NotificationService {
+    pub fn new(db: Database) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(SEND_TIMEOUT_SECONDS))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        let public_url = std::env::var("PUBLIC_URL")
+            .ok()
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+
+        Self {
+            db,
+            client,
+            smtp: SmtpSettings::from_env(),
+            public_url,
+        }
+    }
+
+    /// Send new notifications every 10 seconds
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(DISPATCH_POLL_SECONDS));
+        loop {
+            interval.tick().await;
+            match self.dispatch_pending().await {
+                Ok(0) => {}
+                Ok(sent) => info!("Sent {} notification(s) to outbound channels", sent),
+                Err(e) => warn!("Failed to send notifications to outbound channels: {}", e),
+            }
+        }
+    }
+
+    /// Send the notifications that were not sent yet. Returns how many channels
+    /// accepted one.
+    pub async fn dispatch_pending(&self) -> Result<usize> {
+        let notifications = self.db.claim_undispatched_notifications(DISPATCH_BATCH_SIZE).await?;
+        let mut channels_by_user: HashMap<Uuid, Vec<NotificationChannel>> = HashMap::new();
+        let mut sent = 0;
+
+        for notification in &notifications {
+            let channels = match channels_by_user.entry(notification.user_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.db.get_enabled_notification_channels(notification.user_id).await?),
+            };
+
+            for channel in channels.iter().filter(|channel| channel.accepts(&notification.notification_type)) {
+                if self.send_and_record(channel, notification).await {
+                    sent += 1;
+                }
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Send a test notification to a channel, whatever types it opted into
+    pub async fn send_test(&self, channel: &NotificationChannel) -> Result<()> {
+        let locale = i18n::locale_of(&self.db, channel.user_id).await;
+        let values = [("channel", channel.name.clone())];
+        let notification = Notification {
+            id: Uuid::new_v4(),
+            user_id: channel.user_id,
+            notification_type: "info".to_string(),
+            title: i18n::translate(&locale, "notification.test.title", &values),
+            message: i18n::translate(&locale, "notification.test.message", &values),
+            read: false,
+            action_url: None,
+            metadata: None,
+            created_at: Utc::now(),
+        };
+
+        let result = self.send(channel, &notification).await;
+        let error = result.as_ref().err().map(|e| e.to_string());
+        if let Err(e) = self.db.record_notification_channel_send(channel.id, error.as_deref()).await {
+            warn!("Failed to record send to notification channel {}: {}", channel.id, e);
+        }
+        result
+    }
+
+    async fn send_and_record(&self, channel: &NotificationChannel, notification: &Notification) -> bool {
+        let error = match self.send(channel, notification).await {
+            Ok(()) => None,
+            Err(e) => {
+                warn!("Notification channel {} did not accept notification {}: {}", channel.id, notification.id, e);
+                Some(e.to_string())
+            }
+        };
+
+        if let Err(e) = self.db.record_notification_channel_send(channel.id, error.as_deref()).await {
+            warn!("Failed to record send to notification channel {}: {}", channel.id, e);
+        }
+        error.is_none()
+    }
+
+    async fn send(&self, channel: &NotificationChannel, notification: &Notification) -> Result<()> {
+        let link = self.link(notification);
+        match &channel.config.0 {
+            NotificationChannelConfig::Email { to } => {
+                self.send_email(channel.user_id, to.as_deref(), notification, link.as_deref()).await
+            }
+            NotificationChannelConfig::Ntfy { server_url, topic, token } => {
+                let server_url = server_url.as_deref().unwrap_or(DEFAULT_NTFY_SERVER).trim_end_matches('/');
+                let mut body = json!({
+                    "topic": topic,
+                    "title": notification.title,
+                    "message": notification.message,
+                    "priority": ntfy_priority(&notification.notification_type),
+                    "tags": [notification.notification_type],
+                });
+                if let Some(link) = &link {
+                    body["click"] = json!(link);
+                }
+                let mut request = self.client.post(server_url).json(&body);
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+                check_response(request.send().await?)
+            }
+            NotificationChannelConfig::Gotify { server_url, token } => {
+                let mut body = json!({
+                    "title": notification.title,
+                    "message": notification.message,
+                    "priority": gotify_priority(&notification.notification_type),
+                });
+                if let Some(link) = &link {
+                    body["extras"] = json!({ "client::notification": { "click": { "url": link } } });
+                }
+                let response = self
+                    .client
+                    .post(format!("{}/message", server_url.trim_end_matches('/')))
+                    .header("X-Gotify-Key", token)
+                    .json(&body)
+                    .send()
+                    .await?;
+                check_response(response)
+            }
+            NotificationChannelConfig::Slack { webhook_url } => {
+                let mut text = format!("*{}*\n{}", notification.title, notification.message);
+                if let Some(link) = &link {
+                    let locale = i18n::locale_of(&self.db, channel.user_id).await;
+                    text.push_str(&format!("\n<{}|{}>", link, i18n::translate(&locale, "notification.open_link", &[])));
+                }
+                let response = self.client.post(webhook_url).json(&json!({ "text": text })).send().await?;
+                check_response(response)
+            }
+        }
+    }
+
+    async fn send_email(
+        &self,
+        user_id: Uuid,
+        to: Option<&str>,
+        notification: &Notification,
+        link: Option<&str>,
+    ) -> Result<()> {
+        let mut body = notification.message.clone();
+        if let Some(link) = link {
+            body.push_str(&format!("\n\n{}", link));
+        }
+        self.send_email_message(user_id, to, &notification.title, body).await
+    }
+
+    /// Send a plain text email through the server's SMTP settings, to `to` or else the
+    /// user's email address
+    pub async fn send_email_message(&self, user_id: Uuid, to: Option<&str>, subject: &str, body: String) -> Result<()> {
+        let smtp = self
+            .smtp
+            .as_ref()
+            .ok_or_else(|| anyhow!("Email is not configured on this server (SMTP_HOST and SMTP_FROM_ADDRESS)"))?;
+        let to = match to {
+            Some(to) => to.to_string(),
+            None => {
+                self.db
+                    .get_user_by_id(user_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("User {} no longer exists", user_id))?
+                    .email
+            }
+        };
+
+        let message = Message::builder()
+            .from(smtp.from.parse::<Mailbox>()?)
+            .to(to.parse::<Mailbox>()?)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)?;
+
+        // Port 465 expects TLS from the start, other ports upgrade with STARTTLS
+        let mut transport = match (smtp.use_tls, smtp.port) {
+            (true, 465) => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)?,
+            (true, _) => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?,
+            (false, _) => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+        }
+        .port(smtp.port)
+        .timeout(Some(Duration::from_secs(SEND_TIMEOUT_SECONDS)));
+        if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+            transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        transport.build().send(message).await?;
+        Ok(())
+    }
+
+    /// Base URL of the web interface, from `PUBLIC_URL`
+    pub fn public_url(&self) -> Option<&str> {
+        self.public_url.as_deref()
+    }
+
+    /// Absolute URL of the notification's action, when `PUBLIC_URL` makes one possible
+    fn link(&self, notification: &Notification) -> Option<String> {
+        let action_url = notification.action_url.as_deref()?;
+        if action_url.starts_with("http://") || action_url.starts_with("https://") {
+            return Some(action_url.to_string());
+        }
+        let public_url = self.public_url.as_deref()?;
+        Some(format!("{}/{}", public_url, action_url.trim_start_matches('/')))
+    }
+}
+
+fn check_response(response: reqwest::Response) -> Result<()> {
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(anyhow!("Server responded with {}", response.status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priorities_rank_errors_highest() {
+        assert!(ntfy_priority("error") > ntfy_priority("info"));
+        assert_eq!(ntfy_priority("success"), 3);
+        assert!(gotify_priority("error") > gotify_priority("warning"));
+        assert!(gotify_priority("warning") > gotify_priority("success"));
+    }
+}
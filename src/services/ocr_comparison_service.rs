@@ -0,0 +1,347 @@
+//! Runs two OCR engines over the same documents and scores both against reference
+//! texts, so a change of engine or settings can be judged before it is rolled out.
+//! A comparison runs in the background one document at a time, keeps every result as
+//! it goes and continues after a restart.
+
+use anyhow::{anyhow, Result};
+use std::time::Instant;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::errors::panic::spawn_guarded;
+use crate::models::{
+    Document, OcrComparison, OcrComparisonDocument, OcrComparisonStatus, OcrEngineConfig, Settings, UserRole,
+};
+use crate::ocr::enhanced::EnhancedOcrService;
+use crate::services::file_service::FileService;
+use crate::services::llm::llm_service::LLMService;
+
+/// Characters of each text that are scored; edit distance grows with the square of it
+const MAX_SCORED_CHARS: usize = 10_000;
+/// Pages the vision engine transcribes per document
+#[cfg(feature = "ocr")]
+const VISION_MAX_PAGES: usize = 10;
+#[cfg(feature = "ocr")]
+const VISION_RENDER_DPI: u32 = 150;
+
+/// What one engine made of a document
+#[derive(Debug, Default)]
+struct EngineRun {
+    text: Option<String>,
+    confidence: Option<f32>,
+    duration_ms: Option<i64>,
+    error: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct OcrComparisonService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl OcrComparisonService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Run the comparison in the background
+    pub fn spawn(&self, comparison_id: Uuid) {
+        let service = self.clone();
+        spawn_guarded(format!("OCR comparison {}", comparison_id), async move {
+            if let Err(e) = service.run(comparison_id).await {
+                warn!("OCR comparison {} stopped: {}", comparison_id, e);
+            }
+        });
+    }
+
+    /// Continue the comparisons that were queued or running when the server stopped
+    pub async fn resume_interrupted(&self) -> Result<()> {
+        for comparison in self.db.get_unfinished_ocr_comparisons().await? {
+            info!(
+                "Resuming OCR comparison {} at {} of {} documents",
+                comparison.id, comparison.processed_documents, comparison.total_documents
+            );
+            self.spawn(comparison.id);
+        }
+        Ok(())
+    }
+
+    /// Run both engines on the comparison's remaining documents
+    pub async fn run(&self, comparison_id: Uuid) -> Result<()> {
+        let comparison = self
+            .db
+            .get_ocr_comparison(comparison_id)
+            .await?
+            .ok_or_else(|| anyhow!("OCR comparison {} not found", comparison_id))?;
+        if comparison.status.is_finished() {
+            return Ok(());
+        }
+
+        self.db.start_ocr_comparison(comparison.id).await?;
+        let (status, error) = match self.process(&comparison).await {
+            Ok(()) => (OcrComparisonStatus::Completed, None),
+            Err(e) => (OcrComparisonStatus::Failed, Some(e.to_string())),
+        };
+
+        match &error {
+            None => info!("OCR comparison {} completed", comparison.id),
+            Some(e) => warn!("OCR comparison {} failed: {}", comparison.id, e),
+        }
+        self.db.finish_ocr_comparison(comparison.id, status, error.as_deref()).await?;
+        Ok(())
+    }
+
+    /// A deleted comparison has no documents left, so it stops after the current one
+    async fn process(&self, comparison: &OcrComparison) -> Result<()> {
+        let llm = LLMService::new(self.db.get_pool().clone());
+        for engine in [&comparison.engine_a.0, &comparison.engine_b.0] {
+            if *engine == OcrEngineConfig::Vision && llm.vision_model().is_none() {
+                return Err(anyhow!("No vision model is configured"));
+            }
+        }
+
+        while let Some(mut result) = self.db.get_next_ocr_comparison_document(comparison.id).await? {
+            let document = self
+                .db
+                .get_document_by_id(result.document_id, Uuid::nil(), UserRole::Admin)
+                .await?;
+            let (a, b) = match &document {
+                Some(document) => (
+                    self.read(&llm, comparison, &comparison.engine_a.0, document).await,
+                    self.read(&llm, comparison, &comparison.engine_b.0, document).await,
+                ),
+                None => {
+                    let missing = || EngineRun { error: Some("Document not found".to_string()), ..Default::default() };
+                    (missing(), missing())
+                }
+            };
+
+            result.text_a = a.text;
+            result.confidence_a = a.confidence;
+            result.duration_ms_a = a.duration_ms;
+            result.error_a = a.error;
+            result.text_b = b.text;
+            result.confidence_b = b.confidence;
+            result.duration_ms_b = b.duration_ms;
+            result.error_b = b.error;
+            // Edit distances of long texts take a while
+            let result = tokio::task::spawn_blocking(move || score(result)).await?;
+            self.db.save_ocr_comparison_document(comparison.id, &result).await?;
+        }
+        Ok(())
+    }
+
+    async fn read(
+        &self,
+        llm: &LLMService,
+        comparison: &OcrComparison,
+        engine: &OcrEngineConfig,
+        document: &Document,
+    ) -> EngineRun {
+        let started = Instant::now();
+        let result = match engine {
+            OcrEngineConfig::Tesseract { .. } => self.tesseract(engine, document).await,
+            OcrEngineConfig::Vision => self.vision(llm, comparison, document).await,
+        };
+        let duration_ms = Some(started.elapsed().as_millis() as i64);
+        match result {
+            Ok((text, confidence)) => EngineRun { text: Some(text), confidence, duration_ms, error: None },
+            Err(e) => {
+                warn!("OCR comparison {} could not read document {}: {}", comparison.id, document.id, e);
+                EngineRun { duration_ms, error: Some(e.to_string()), ..Default::default() }
+            }
+        }
+    }
+
+    /// OCR with the settings the document would normally get, changed by the engine's
+    async fn tesseract(&self, engine: &OcrEngineConfig, document: &Document) -> Result<(String, Option<f32>)> {
+        let mut settings = self.db.get_user_settings(document.user_id).await.ok().flatten().unwrap_or_default();
+        if let Some(workspace_settings) = self.db.get_document_workspace_settings(document.id).await? {
+            workspace_settings.apply_to(&mut settings);
+        }
+        apply_engine_settings(engine, &mut settings);
+
+        let ocr_service = EnhancedOcrService::new("/tmp".to_string(), self.file_service.clone());
+        let plaintext = self.file_service.plaintext_path(&document.file_path).await?;
+        let ocr = ocr_service
+            .extract_text_with_context(
+                plaintext.path(),
+                &document.mime_type,
+                &document.original_filename,
+                document.file_size,
+                &settings,
+            )
+            .await?;
+        Ok((ocr.text, Some(ocr.confidence)))
+    }
+
+    /// Transcribe the pages with the vision model; it gives no confidence
+    #[cfg(feature = "ocr")]
+    async fn vision(
+        &self,
+        llm: &LLMService,
+        comparison: &OcrComparison,
+        document: &Document,
+    ) -> Result<(String, Option<f32>)> {
+        use crate::models::LLM_FEATURE_VISION_OCR;
+        use crate::ocr::page_images;
+        use crate::services::vision_fallback_service::TRANSCRIBE_PROMPT;
+
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let pages = page_images::render_pages(&data, &document.mime_type, VISION_RENDER_DPI, VISION_MAX_PAGES).await?;
+        if pages.is_empty() {
+            return Err(anyhow!("No pages to transcribe"));
+        }
+
+        let mut texts = Vec::with_capacity(pages.len());
+        for page in &pages {
+            let png = page_images::encode_png(page)?;
+            let text = llm
+                .describe_image(&png, TRANSCRIBE_PROMPT)
+                .await
+                .map_err(|e| anyhow!(e))?
+                .ok_or_else(|| anyhow!("No vision model is configured"))?;
+            // Pages are charged to whoever started the comparison
+            if let Err(e) = self
+                .db
+                .record_llm_usage(comparison.user_id, LLM_FEATURE_VISION_OCR, 1, Some(document.id))
+                .await
+            {
+                warn!("Failed to record LLM usage of document {}: {}", document.id, e);
+            }
+            texts.push(text);
+        }
+        Ok((texts.join("\n\n").trim().to_string(), None))
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    async fn vision(
+        &self,
+        _llm: &LLMService,
+        _comparison: &OcrComparison,
+        _document: &Document,
+    ) -> Result<(String, Option<f32>)> {
+        Err(anyhow!("Vision transcription requires OCR feature"))
+    }
+}
+
+fn apply_engine_settings(engine: &OcrEngineConfig, settings: &mut Settings) {
+    if let OcrEngineConfig::Tesseract { language, page_segmentation_mode, engine_mode, image_preprocessing, dpi } = engine {
+        if let Some(language) = language {
+            settings.ocr_language = language.clone();
+        }
+        if let Some(psm) = page_segmentation_mode {
+            settings.ocr_page_segmentation_mode = *psm;
+        }
+        if let Some(oem) = engine_mode {
+            settings.ocr_engine_mode = *oem;
+        }
+        if let Some(preprocessing) = image_preprocessing {
+            settings.enable_image_preprocessing = *preprocessing;
+        }
+        if let Some(dpi) = dpi {
+            settings.ocr_dpi = *dpi;
+        }
+    }
+}
+
+/// Score both texts against the reference text, or against each other without one
+fn score(mut result: OcrComparisonDocument) -> OcrComparisonDocument {
+    match &result.ground_truth {
+        Some(reference) => {
+            if let Some(text) = &result.text_a {
+                result.character_accuracy_a = Some(character_accuracy(reference, text));
+                result.word_accuracy_a = Some(word_accuracy(reference, text));
+            }
+            if let Some(text) = &result.text_b {
+                result.character_accuracy_b = Some(character_accuracy(reference, text));
+                result.word_accuracy_b = Some(word_accuracy(reference, text));
+            }
+        }
+        None => {
+            if let (Some(a), Some(b)) = (&result.text_a, &result.text_b) {
+                result.agreement = Some(character_accuracy(a, b));
+            }
+        }
+    }
+    result
+}
+
+/// Fewest insertions, deletions and substitutions turning `a` into `b`
+fn levenshtein<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Share of the reference that came out right, from 0 to 1
+fn accuracy<T: PartialEq>(reference: &[T], hypothesis: &[T]) -> f64 {
+    if reference.is_empty() {
+        return if hypothesis.is_empty() { 1.0 } else { 0.0 };
+    }
+    (1.0 - levenshtein(reference, hypothesis) as f64 / reference.len() as f64).max(0.0)
+}
+
+/// Characters with runs of whitespace as one space, since layout is not what is compared
+fn normalized_chars(text: &str) -> Vec<char> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words.join(" ").chars().take(MAX_SCORED_CHARS).collect()
+}
+
+/// One minus the character error rate
+pub fn character_accuracy(reference: &str, hypothesis: &str) -> f64 {
+    accuracy(&normalized_chars(reference), &normalized_chars(hypothesis))
+}
+
+/// One minus the word error rate
+pub fn word_accuracy(reference: &str, hypothesis: &str) -> f64 {
+    let words = |text: &str| -> Vec<String> {
+        let chars: String = normalized_chars(text).into_iter().collect();
+        chars.split(' ').filter(|word| !word.is_empty()).map(str::to_string).collect()
+    };
+    accuracy(&words(reference), &words(hypothesis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        let chars = |text: &str| text.chars().collect::<Vec<_>>();
+        assert_eq!(levenshtein(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(levenshtein(&chars(""), &chars("abc")), 3);
+        assert_eq!(levenshtein(&chars("same"), &chars("same")), 0);
+    }
+
+    #[test]
+    fn test_accuracy() {
+        assert_eq!(character_accuracy("Invoice  total\n42", "Invoice total 42"), 1.0);
+        assert_eq!(character_accuracy("abcd", "abXd"), 0.75);
+        assert_eq!(character_accuracy("ab", "completely different"), 0.0);
+        assert_eq!(character_accuracy("", ""), 1.0);
+        assert_eq!(word_accuracy("the total is 42", "the tota1 is 42"), 0.75);
+        assert_eq!(word_accuracy("due 2025", ""), 0.0);
+    }
+
+    #[test]
+    fn test_score_without_reference() {
+        let document = OcrComparisonDocument {
+            text_a: Some("abcd".to_string()),
+            text_b: Some("abcx".to_string()),
+            ..Default::default()
+        };
+        let scored = score(document);
+        assert_eq!(scored.agreement, Some(0.75));
+        assert_eq!(scored.character_accuracy_a, None);
+    }
+}
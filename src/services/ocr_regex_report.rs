@@ -0,0 +1,231 @@
+use anyhow::{anyhow, Result};
+use futures::TryStreamExt;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use sqlx::{Postgres, QueryBuilder, Row};
+use uuid::Uuid;
+
+use crate::db::documents::INDEXED_TEXT;
+use crate::db::Database;
+
+#[derive(Debug, Clone)]
+pub struct RegexReportOptions {
+    pub pattern: String,
+    pub case_insensitive: bool,
+    /// Characters of context kept on each side of a match
+    pub context_chars: usize,
+    pub user_id: Option<Uuid>,
+    /// Stop reporting a document's matches after this many; 0 reports all
+    pub max_matches_per_document: usize,
+    /// Read every document instead of prefiltering in Postgres
+    pub full_scan: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TextMatch {
+    /// `content` or `ocr_text`
+    pub field: &'static str,
+    /// Byte offset of the match in the field
+    pub offset: usize,
+    pub matched: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentMatch {
+    pub document_id: Uuid,
+    pub user_id: Uuid,
+    pub filename: String,
+    #[serde(flatten)]
+    pub text_match: TextMatch,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RegexReportSummary {
+    /// Whether Postgres prefiltered the documents with the pattern
+    pub index_assisted: bool,
+    pub documents_scanned: u64,
+    pub documents_matched: u64,
+    pub matches: u64,
+}
+
+pub fn build_regex(options: &RegexReportOptions) -> Result<Regex> {
+    RegexBuilder::new(&options.pattern)
+        .case_insensitive(options.case_insensitive)
+        .build()
+        .map_err(|e| anyhow!("Invalid pattern: {}", e))
+}
+
+/// Stream every document's text through the pattern, calling `on_match` as matches
+/// are found so large archives are reported without being held in memory
+pub async fn run<F>(db: &Database, options: &RegexReportOptions, mut on_match: F) -> Result<RegexReportSummary>
+where
+    F: FnMut(DocumentMatch) -> Result<()>,
+{
+    let regex = build_regex(options)?;
+    let mut summary = RegexReportSummary {
+        index_assisted: !options.full_scan && postgres_accepts(db, &options.pattern).await,
+        ..Default::default()
+    };
+
+    let mut query = QueryBuilder::<Postgres>::new(
+        "SELECT id, user_id, original_filename, content, ocr_text FROM documents WHERE (content IS NOT NULL OR ocr_text IS NOT NULL)"
+    );
+    if let Some(user_id) = options.user_id {
+        query.push(" AND user_id = ");
+        query.push_bind(user_id);
+    }
+    if summary.index_assisted {
+        query.push(format!(" AND {} {} ", INDEXED_TEXT, if options.case_insensitive { "~*" } else { "~" }));
+        query.push_bind(options.pattern.clone());
+    }
+    query.push(" ORDER BY created_at");
+
+    let mut rows = query.build().fetch(db.get_pool());
+    while let Some(row) = rows.try_next().await? {
+        summary.documents_scanned += 1;
+        let document_id: Uuid = row.get("id");
+        let user_id: Uuid = row.get("user_id");
+        let filename: String = row.get("original_filename");
+
+        let mut reported = 0;
+        for (field, text) in [("content", row.get::<Option<String>, _>("content")), ("ocr_text", row.get("ocr_text"))] {
+            let Some(text) = text else { continue };
+            let remaining = match options.max_matches_per_document {
+                0 => 0,
+                max if reported >= max => break,
+                max => max - reported,
+            };
+            for text_match in find_matches(&regex, field, &text, options.context_chars, remaining) {
+                reported += 1;
+                on_match(DocumentMatch {
+                    document_id,
+                    user_id,
+                    filename: filename.clone(),
+                    text_match,
+                })?;
+            }
+        }
+
+        if reported > 0 {
+            summary.documents_matched += 1;
+            summary.matches += reported as u64;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Matches in `text` with surrounding context; `limit` of 0 returns all of them
+pub fn find_matches(regex: &Regex, field: &'static str, text: &str, context_chars: usize, limit: usize) -> Vec<TextMatch> {
+    regex
+        .find_iter(text)
+        .filter(|m| !m.is_empty())
+        .take(if limit == 0 { usize::MAX } else { limit })
+        .map(|m| TextMatch {
+            field,
+            offset: m.start(),
+            matched: m.as_str().to_string(),
+            before: single_line(tail_chars(&text[..m.start()], context_chars)),
+            after: single_line(head_chars(&text[m.end()..], context_chars)),
+        })
+        .collect()
+}
+
+/// Whether the pattern can prefilter rows in Postgres without dropping any document
+/// the Rust regex would match. Postgres' regex dialect differs in its escapes, inline
+/// flags and anchors, so only patterns that mean the same in both are sent.
+pub fn is_portable_to_postgres(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('d' | 'D' | 's' | 'S' | 'w' | 'W') => {}
+                Some(escaped) if !escaped.is_alphanumeric() => {}
+                _ => return false,
+            },
+            // Anchors: the indexed expression joins two fields, so `^`/`$` of one field
+            // is not the start or end of the indexed text
+            '^' | '$' => return false,
+            // Inline flags and non-capturing groups use syntax Postgres reads differently
+            '(' if chars.clone().next() == Some('?') => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Postgres also has to accept the pattern; a rejected one falls back to a full scan
+async fn postgres_accepts(db: &Database, pattern: &str) -> bool {
+    if !is_portable_to_postgres(pattern) {
+        return false;
+    }
+    sqlx::query("SELECT '' ~ $1")
+        .bind(pattern)
+        .execute(db.get_pool())
+        .await
+        .is_ok()
+}
+
+fn tail_chars(text: &str, count: usize) -> &str {
+    if count == 0 {
+        return "";
+    }
+    match text.char_indices().rev().nth(count - 1) {
+        Some((index, _)) => &text[index..],
+        None => text,
+    }
+}
+
+fn head_chars(text: &str, count: usize) -> &str {
+    match text.char_indices().nth(count) {
+        Some((index, _)) => &text[..index],
+        None => text,
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches_with_context() {
+        let regex = Regex::new(r"AKIA[0-9A-Z]{4}").unwrap();
+        let text = "key:\nAKIA1234 and AKIA5678 — done";
+
+        let matches = find_matches(&regex, "ocr_text", text, 5, 0);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].matched, "AKIA1234");
+        assert_eq!(matches[0].offset, 5);
+        assert_eq!(matches[0].before, "key:");
+        assert_eq!(matches[0].after, "and");
+        assert_eq!(matches[1].after, "— do");
+
+        assert_eq!(find_matches(&regex, "ocr_text", text, 5, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_context_respects_char_boundaries() {
+        assert_eq!(tail_chars("ääbb", 3), "äbb");
+        assert_eq!(tail_chars("ab", 5), "ab");
+        assert_eq!(tail_chars("ab", 0), "");
+        assert_eq!(head_chars("ääbb", 3), "ääb");
+        assert_eq!(head_chars("ab", 5), "ab");
+    }
+
+    #[test]
+    fn test_postgres_portability() {
+        assert!(is_portable_to_postgres(r"INV-\d{6}"));
+        assert!(is_portable_to_postgres(r"password\s*[:=]\s*\S+"));
+        assert!(is_portable_to_postgres(r"Main St\.|Elm Rd\."));
+        assert!(!is_portable_to_postgres(r"\bfoo\b"));
+        assert!(!is_portable_to_postgres(r"(?i)secret"));
+        assert!(!is_portable_to_postgres(r"^Invoice"));
+        assert!(!is_portable_to_postgres(r"\p{Lu}+"));
+    }
+}
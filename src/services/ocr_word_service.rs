@@ -0,0 +1,102 @@
+use anyhow::Result;
+#[cfg(feature = "ocr")]
+use tracing::info;
+
+use crate::db::Database;
+use crate::models::{Document, DEFAULT_LOW_CONFIDENCE_THRESHOLD};
+#[cfg(feature = "ocr")]
+use crate::models::CreateOcrPageWords;
+use crate::services::file_service::FileService;
+
+/// Pages beyond this are not analyzed word by word
+const DEFAULT_MAX_PAGES: usize = 20;
+/// Resolution pages are rasterized at; Tesseract reads best around 300 DPI
+#[cfg(feature = "ocr")]
+const RENDER_DPI: u32 = 300;
+
+/// Records how confident Tesseract was of every word on a document's pages, so
+/// uncertain words can be marked and corrected first
+pub struct OcrWordService {
+    db: Database,
+    file_service: FileService,
+    max_pages: usize,
+}
+
+impl OcrWordService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        let max_pages = std::env::var("OCR_WORD_CONFIDENCE_MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_PAGES);
+
+        Self { db, file_service, max_pages }
+    }
+
+    /// Whether word confidences should be recorded automatically after OCR
+    pub fn enabled_after_ocr() -> bool {
+        std::env::var("OCR_WORD_CONFIDENCE_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false)
+    }
+
+    /// Confidence (0-100) below which words are flagged when a request does not say
+    pub fn default_threshold() -> f32 {
+        std::env::var("OCR_LOW_CONFIDENCE_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<f32>().ok())
+            .filter(|threshold| (0.0..=100.0).contains(threshold))
+            .unwrap_or(DEFAULT_LOW_CONFIDENCE_THRESHOLD)
+    }
+
+    /// Recognize the words of every page in `language`, replacing earlier results.
+    /// Returns the number of pages analyzed.
+    #[cfg(feature = "ocr")]
+    pub async fn analyze_document(&self, document: &Document, language: &str) -> Result<usize> {
+        use crate::models::{BoundingBox, OcrWordConfidence};
+        use crate::ocr::page_images;
+        use crate::services::redaction_service::parse_tesseract_tsv;
+
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let page_count = if document.mime_type == "application/pdf" {
+            crate::ocr::pdf_pages::TempPdf::from_bytes(&data).await?.page_count().await?
+        } else {
+            1
+        };
+        let page_count = page_count.min(self.max_pages as u32);
+
+        let mut pages = Vec::with_capacity(page_count as usize);
+        // One page at a time, so long documents do not have to fit in memory at 300 DPI
+        for page_number in 1..=page_count {
+            let Some(image) = page_images::render_page_range(&data, &document.mime_type, RENDER_DPI, page_number, page_number)
+                .await?
+                .into_iter()
+                .next()
+            else {
+                break;
+            };
+            let words = parse_tesseract_tsv(&page_images::ocr_image_tsv(&image, language).await?)
+                .into_iter()
+                .map(|word| OcrWordConfidence {
+                    text: word.text,
+                    confidence: word.confidence,
+                    bbox: BoundingBox { x: word.left, y: word.top, width: word.width, height: word.height },
+                })
+                .collect();
+            pages.push(CreateOcrPageWords {
+                page_number: page_number as i32,
+                page_width: image.width(),
+                page_height: image.height(),
+                words,
+            });
+        }
+
+        self.db.replace_ocr_page_words(document.id, &pages).await?;
+        info!("Recorded word confidences of {} pages of document {}", pages.len(), document.id);
+        Ok(pages.len())
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub async fn analyze_document(&self, _document: &Document, _language: &str) -> Result<usize> {
+        anyhow::bail!("Word confidence analysis requires OCR feature")
+    }
+}
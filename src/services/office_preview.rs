@@ -0,0 +1,88 @@
+//! PDF renditions of office documents via headless LibreOffice, used for
+//! thumbnails and in-browser previews of formats browsers cannot display.
+
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::services::converter_sandbox::SandboxedCommand;
+
+/// Extensions LibreOffice can render to PDF
+const OFFICE_EXTENSIONS: &[&str] = &[
+    "doc", "docx", "odt", "rtf", "xls", "xlsx", "ods", "ppt", "pptx", "odp",
+];
+const DEFAULT_TIMEOUT_SECONDS: u64 = 60;
+
+pub fn is_office_extension(extension: &str) -> bool {
+    OFFICE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+}
+
+/// Convert an office document to PDF. `LIBREOFFICE_PATH` overrides the
+/// `soffice` binary and `OFFICE_PREVIEW_TIMEOUT_SECONDS` bounds the conversion, which
+/// runs in the converter sandbox.
+pub async fn convert_to_pdf(data: &[u8], extension: &str) -> Result<Vec<u8>> {
+    if !is_office_extension(extension) {
+        return Err(anyhow!("Cannot render .{} files to PDF", extension));
+    }
+
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let work_dir = PathBuf::from(temp_dir).join(format!("office_preview_{}", Uuid::new_v4()));
+    tokio::fs::create_dir_all(&work_dir).await?;
+
+    let result = convert_in(&work_dir, data, &extension.to_lowercase()).await;
+    let _ = tokio::fs::remove_dir_all(&work_dir).await;
+    result
+}
+
+async fn convert_in(work_dir: &std::path::Path, data: &[u8], extension: &str) -> Result<Vec<u8>> {
+    let input = work_dir.join(format!("document.{}", extension));
+    tokio::fs::write(&input, data).await?;
+
+    let binary = std::env::var("LIBREOFFICE_PATH").unwrap_or_else(|_| "soffice".to_string());
+    let timeout = std::env::var("OFFICE_PREVIEW_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECONDS);
+
+    // A private profile directory lets conversions run concurrently
+    let profile = format!("-env:UserInstallation=file://{}", work_dir.join("profile").display());
+    let output = SandboxedCommand::new(&binary)
+        .arg(&profile)
+        .arg("--headless")
+        .arg("--convert-to")
+        .arg("pdf")
+        .arg("--outdir")
+        .arg(work_dir)
+        .arg(&input)
+        .current_dir(work_dir)
+        .input(data)
+        .timeout(Duration::from_secs(timeout))
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Office conversion failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    tokio::fs::read(work_dir.join("document.pdf"))
+        .await
+        .map_err(|e| anyhow!("Office conversion produced no PDF: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_office_extensions() {
+        assert!(is_office_extension("DOCX"));
+        assert!(is_office_extension("odt"));
+        assert!(is_office_extension("pptx"));
+        assert!(!is_office_extension("pdf"));
+        assert!(!is_office_extension("png"));
+    }
+}
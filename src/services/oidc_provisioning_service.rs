@@ -0,0 +1,197 @@
+//! Keeps OIDC users' roles and group memberships in line with the groups their
+//! identity provider reports, on every login.
+//!
+//! - `OIDC_GROUPS_CLAIM`: userinfo claim listing the user's groups, default `groups`.
+//!   A dotted path reaches into nested claims, e.g. `realm_access.roles` for Keycloak.
+//! - `OIDC_ADMIN_GROUPS`: comma separated IdP groups whose members are admins. When set,
+//!   every other OIDC user is made a regular user on login; when unset roles are left
+//!   alone.
+//! - `OIDC_GROUP_MAPPING`: comma separated `idp-group=readur-group` pairs. Members of the
+//!   IdP group are added to the readur group, created if needed, and removed from it
+//!   once they leave the IdP group. Groups not named here are never touched.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use tracing::info;
+
+use crate::db::Database;
+use crate::models::{User, UserRole};
+use crate::oidc::OidcUserInfo;
+use crate::services::audit_service::{self, AuditEvent, ClientInfo};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OidcClaimMapping {
+    pub groups_claim: String,
+    pub admin_groups: Vec<String>,
+    /// (IdP group, readur group)
+    pub group_mappings: Vec<(String, String)>,
+}
+
+static CLAIM_MAPPING: Lazy<OidcClaimMapping> = Lazy::new(|| {
+    OidcClaimMapping::parse(
+        std::env::var("OIDC_GROUPS_CLAIM").ok().as_deref(),
+        std::env::var("OIDC_ADMIN_GROUPS").ok().as_deref(),
+        std::env::var("OIDC_GROUP_MAPPING").ok().as_deref(),
+    )
+});
+
+fn split_list(value: Option<&str>) -> impl Iterator<Item = &str> {
+    value
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+impl OidcClaimMapping {
+    pub fn parse(groups_claim: Option<&str>, admin_groups: Option<&str>, group_mapping: Option<&str>) -> Self {
+        OidcClaimMapping {
+            groups_claim: groups_claim
+                .map(str::trim)
+                .filter(|claim| !claim.is_empty())
+                .unwrap_or("groups")
+                .to_string(),
+            admin_groups: split_list(admin_groups).map(str::to_string).collect(),
+            group_mappings: split_list(group_mapping)
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(idp, readur)| (idp.trim().to_string(), readur.trim().to_string()))
+                .filter(|(idp, readur)| !idp.is_empty() && !readur.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Groups the user belongs to according to the configured claim. A single string
+    /// counts as one group; anything else as none.
+    pub fn groups(&self, user_info: &OidcUserInfo) -> Vec<String> {
+        let mut path = self.groups_claim.split('.');
+        let first = path.next().and_then(|key| user_info.claims.get(key));
+        let claim = path.fold(first, |value, key| value.and_then(|v| v.get(key)));
+
+        match claim {
+            Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+            Some(Value::String(group)) => vec![group.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The role the groups call for, or None when roles are not managed by the IdP
+    pub fn role(&self, groups: &[String]) -> Option<UserRole> {
+        if self.admin_groups.is_empty() {
+            return None;
+        }
+        let is_admin = self.admin_groups.iter().any(|admin| groups.contains(admin));
+        Some(if is_admin { UserRole::Admin } else { UserRole::User })
+    }
+
+    /// Every mapped readur group, with whether the user should be a member. A readur
+    /// group several IdP groups map to needs only one of them.
+    pub fn memberships(&self, groups: &[String]) -> Vec<(String, bool)> {
+        let mut memberships: Vec<(String, bool)> = Vec::new();
+        for (idp_group, readur_group) in &self.group_mappings {
+            let member = groups.contains(idp_group);
+            match memberships.iter_mut().find(|(name, _)| name == readur_group) {
+                Some((_, existing)) => *existing |= member,
+                None => memberships.push((readur_group.clone(), member)),
+            }
+        }
+        memberships
+    }
+}
+
+pub fn claim_mapping() -> &'static OidcClaimMapping {
+    &CLAIM_MAPPING
+}
+
+/// Apply the configured role and group mapping to a user who just logged in through
+/// OIDC, returning the user with their current role
+pub async fn sync_user(db: &Database, mut user: User, user_info: &OidcUserInfo, client: &ClientInfo) -> Result<User> {
+    let mapping = claim_mapping();
+    let groups = mapping.groups(user_info);
+
+    if let Some(role) = mapping.role(&groups) {
+        if role != user.role {
+            db.update_user_role(user.id, role).await?;
+            info!("OIDC groups changed the role of user {} from {} to {}", user.username, user.role, role);
+            audit_service::record(
+                db,
+                None,
+                client,
+                AuditEvent::new(audit_service::USER_ROLE_CHANGE, "user", Some(user.id))
+                    .before(serde_json::json!({ "role": user.role }))
+                    .after(serde_json::json!({ "role": role }))
+                    .details(serde_json::json!({ "source": "oidc", "groups": groups })),
+            )
+            .await;
+            user.role = role;
+        }
+    }
+
+    for (group_name, member) in mapping.memberships(&groups) {
+        if member {
+            let group_id = db.ensure_user_group(&group_name).await?;
+            if db.add_user_group_member(group_id, user.id).await? {
+                info!("OIDC groups added user {} to group '{}'", user.username, group_name);
+            }
+        } else if let Some(group) = db.get_user_group_by_name(&group_name).await? {
+            if db.remove_user_group_member(group.id, user.id).await? {
+                info!("OIDC groups removed user {} from group '{}'", user.username, group_name);
+            }
+        }
+    }
+
+    Ok(user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn user_info(claims: Value) -> OidcUserInfo {
+        let mut info = json!({ "sub": "abc123" });
+        info.as_object_mut().unwrap().extend(claims.as_object().unwrap().clone());
+        serde_json::from_value(info).unwrap()
+    }
+
+    #[test]
+    fn test_parse_mapping() {
+        let mapping = OidcClaimMapping::parse(None, Some("readur-admins, ops ,"), Some("finance=Accounting, bad, hr=HR"));
+        assert_eq!(mapping.groups_claim, "groups");
+        assert_eq!(mapping.admin_groups, vec!["readur-admins", "ops"]);
+        assert_eq!(
+            mapping.group_mappings,
+            vec![
+                ("finance".to_string(), "Accounting".to_string()),
+                ("hr".to_string(), "HR".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_groups_from_nested_claim() {
+        let mapping = OidcClaimMapping::parse(Some("realm_access.roles"), None, None);
+        let info = user_info(json!({ "realm_access": { "roles": ["admin", "user"] } }));
+        assert_eq!(mapping.groups(&info), vec!["admin", "user"]);
+
+        let missing = user_info(json!({ "groups": ["admin"] }));
+        assert!(mapping.groups(&missing).is_empty());
+    }
+
+    #[test]
+    fn test_role_from_groups() {
+        let unmanaged = OidcClaimMapping::parse(None, None, None);
+        assert_eq!(unmanaged.role(&["admins".to_string()]), None);
+
+        let managed = OidcClaimMapping::parse(None, Some("admins"), None);
+        assert_eq!(managed.role(&["admins".to_string()]), Some(UserRole::Admin));
+        assert_eq!(managed.role(&["staff".to_string()]), Some(UserRole::User));
+    }
+
+    #[test]
+    fn test_memberships_merge_mappings_to_the_same_group() {
+        let mapping = OidcClaimMapping::parse(None, None, Some("finance=Accounting,audit=Accounting,hr=HR"));
+        let memberships = mapping.memberships(&["audit".to_string()]);
+        assert_eq!(memberships, vec![("Accounting".to_string(), true), ("HR".to_string(), false)]);
+    }
+}
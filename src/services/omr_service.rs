@@ -0,0 +1,204 @@
+use anyhow::Result;
+use tracing::info;
+
+use crate::db::Database;
+use crate::models::{best_omr_template, omr_data, Document, OmrRegionReading, OmrResult, OmrTemplate};
+use crate::services::file_service::FileService;
+
+/// Resolution forms are rendered at; enough to see pen marks in small boxes
+#[cfg(feature = "ocr")]
+const OMR_DPI: u32 = 200;
+/// Share of a checkbox cut from each side before counting, so its printed border
+/// does not count as a mark
+#[cfg(feature = "ocr")]
+const CHECKBOX_INSET: f64 = 0.15;
+/// Gray levels below this count as ink
+#[cfg(feature = "ocr")]
+const DARK_PIXEL_LEVEL: u8 = 128;
+
+/// Reads scanned forms with admin-defined templates: whether each checkbox is marked
+/// and what is written in each field, stored as one structured result per document
+pub struct OmrService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl OmrService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Whether scanned documents should be matched against templates after OCR.
+    /// Nothing is read until an admin defines a template.
+    pub fn enabled_after_ocr() -> bool {
+        std::env::var("OMR_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(true)
+    }
+
+    /// The enabled template whose match phrases identify the document's text best
+    pub async fn detect(&self, document: &Document) -> Result<Option<OmrTemplate>> {
+        let Some(text) = document
+            .ocr_text
+            .as_deref()
+            .or(document.content.as_deref())
+            .filter(|t| !t.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+        let templates = self.db.get_enabled_omr_templates().await?;
+        Ok(best_omr_template(&templates, text).cloned())
+    }
+
+    /// Read a matching template from the document; None when no template matches
+    pub async fn process_document(&self, document: &Document, language: &str) -> Result<Option<OmrResult>> {
+        match self.detect(document).await? {
+            Some(template) => self.extract(document, &template, language).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Read the template's regions from the document and store the result. None when
+    /// the document has no page the template could be read on.
+    pub async fn extract(&self, document: &Document, template: &OmrTemplate, language: &str) -> Result<Option<OmrResult>> {
+        let Some(readings) = self.read_regions(document, template, language).await? else {
+            return Ok(None);
+        };
+        let data = omr_data(&template.regions, &readings);
+        let result = self.db.upsert_omr_result(document.id, template.id, &data, &readings).await?;
+
+        let marked = readings.iter().filter(|reading| reading.marked == Some(true)).count();
+        info!(
+            "Read form template '{}' from document {}: {} fields, {} marks",
+            template.name,
+            document.id,
+            data.len(),
+            marked
+        );
+        Ok(Some(result))
+    }
+
+    #[cfg(feature = "ocr")]
+    async fn read_regions(
+        &self,
+        document: &Document,
+        template: &OmrTemplate,
+        language: &str,
+    ) -> Result<Option<Vec<OmrRegionReading>>> {
+        use crate::models::OmrRegionKind;
+        use crate::ocr::page_images;
+        use crate::services::region_ocr_service::PageRegion;
+
+        let page = template.page.max(1) as u32;
+        if document.mime_type.starts_with("image/") && page != 1 {
+            return Ok(None);
+        }
+        if !document.mime_type.starts_with("image/") && document.mime_type != "application/pdf" {
+            return Ok(None);
+        }
+
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let rendered = page_images::render_page_range(&data, &document.mime_type, OMR_DPI, page, page).await?;
+        let Some(image) = rendered.into_iter().next() else {
+            return Ok(None);
+        };
+        let gray = image.to_luma8();
+
+        let mut readings = Vec::with_capacity(template.regions.len());
+        for region in template.regions.iter() {
+            let rect = PageRegion { x: region.x, y: region.y, width: region.width, height: region.height }
+                .to_pixels(image.width(), image.height());
+            let reading = match region.kind {
+                OmrRegionKind::Checkbox => {
+                    let fill_ratio = rect.map(|rect| fill_ratio(&gray, rect)).unwrap_or(0.0);
+                    OmrRegionReading {
+                        key: region.key.clone(),
+                        kind: region.kind,
+                        fill_ratio: Some(fill_ratio),
+                        marked: Some(fill_ratio >= template.fill_threshold),
+                        text: None,
+                    }
+                }
+                OmrRegionKind::Text => {
+                    let text = match rect {
+                        Some((x, y, width, height)) => {
+                            let crop = image.crop_imm(x, y, width, height);
+                            Some(page_images::ocr_image(&crop, language).await?.trim().to_string())
+                        }
+                        None => None,
+                    };
+                    OmrRegionReading {
+                        key: region.key.clone(),
+                        kind: region.kind,
+                        fill_ratio: None,
+                        marked: None,
+                        text,
+                    }
+                }
+            };
+            readings.push(reading);
+        }
+        Ok(Some(readings))
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    async fn read_regions(
+        &self,
+        _document: &Document,
+        _template: &OmrTemplate,
+        _language: &str,
+    ) -> Result<Option<Vec<OmrRegionReading>>> {
+        anyhow::bail!("Optical mark recognition requires OCR feature")
+    }
+}
+
+/// Share of dark pixels inside the pixel rectangle, without its border
+#[cfg(feature = "ocr")]
+fn fill_ratio(gray: &image::GrayImage, (x, y, width, height): (u32, u32, u32, u32)) -> f32 {
+    let inset_x = (width as f64 * CHECKBOX_INSET).round() as u32;
+    let inset_y = (height as f64 * CHECKBOX_INSET).round() as u32;
+    let (left, right) = (x + inset_x, x + width - inset_x);
+    let (top, bottom) = (y + inset_y, y + height - inset_y);
+    if right <= left || bottom <= top {
+        return 0.0;
+    }
+
+    let mut dark = 0u64;
+    for py in top..bottom {
+        for px in left..right {
+            if gray.get_pixel(px, py)[0] < DARK_PIXEL_LEVEL {
+                dark += 1;
+            }
+        }
+    }
+    dark as f32 / ((right - left) as u64 * (bottom - top) as u64) as f32
+}
+
+#[cfg(all(test, feature = "ocr"))]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    #[test]
+    fn test_fill_ratio_ignores_border() {
+        // An empty 20x20 box with a 2 pixel border
+        let mut gray = GrayImage::from_pixel(100, 100, Luma([255]));
+        for y in 10..30 {
+            for x in 10..30 {
+                if x < 12 || x >= 28 || y < 12 || y >= 28 {
+                    gray.put_pixel(x, y, Luma([0]));
+                }
+            }
+        }
+        assert_eq!(fill_ratio(&gray, (10, 10, 20, 20)), 0.0);
+
+        // A cross through the middle
+        for i in 13..27 {
+            gray.put_pixel(i, i, Luma([20]));
+            gray.put_pixel(i, 39 - i, Luma([20]));
+        }
+        let ratio = fill_ratio(&gray, (10, 10, 20, 20));
+        assert!(ratio > 0.1 && ratio < 0.2, "ratio {}", ratio);
+        assert_eq!(fill_ratio(&gray, (0, 0, 1, 1)), 0.0);
+    }
+}
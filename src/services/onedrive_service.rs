@@ -0,0 +1,252 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Response, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::models::{OneDriveSourceConfig, OutboundScope};
+use crate::services::cloud_drive::{relative_to_root, CloudDriveClient, CloudDriveError, CloudFile, DeltaPage, OAuthToken};
+use crate::services::outbound_limits;
+
+const GRAPH_URL: &str = "https://graph.microsoft.com/v1.0";
+const SCOPES: &str = "offline_access Files.Read.All Sites.Read.All";
+
+/// OneDrive and SharePoint source client built on Microsoft Graph's drive delta
+pub struct OneDriveService {
+    config: OneDriveSourceConfig,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: i64,
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeltaResponse {
+    value: Vec<DriveItem>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+    #[serde(rename = "@odata.deltaLink")]
+    delta_link: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriveItem {
+    id: String,
+    name: Option<String>,
+    size: Option<i64>,
+    last_modified_date_time: Option<DateTime<Utc>>,
+    file: Option<FileFacet>,
+    deleted: Option<serde_json::Value>,
+    parent_reference: Option<ParentReference>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileFacet {
+    hashes: Option<Hashes>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Hashes {
+    quick_xor_hash: Option<String>,
+    sha256_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ParentReference {
+    /// e.g. `/drive/root:/Documents/Scans`; absent for the root itself
+    path: Option<String>,
+}
+
+impl OneDriveService {
+    pub fn new(config: OneDriveSourceConfig) -> Result<Self> {
+        if config.client_id.trim().is_empty() {
+            return Err(anyhow!("OneDrive client_id is required"));
+        }
+        if config.refresh_token.trim().is_empty() {
+            return Err(anyhow!("OneDrive refresh_token is required"));
+        }
+        crate::services::cloud_drive::PathFilter::new(&config.include_patterns, &config.exclude_patterns)?;
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(300))
+            .build()?;
+
+        Ok(Self { config, client })
+    }
+
+    fn drive_url(&self) -> String {
+        match self.config.drive_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+            Some(drive_id) => format!("{}/drives/{}", GRAPH_URL, urlencoding::encode(drive_id)),
+            None => format!("{}/me/drive", GRAPH_URL),
+        }
+    }
+
+    async fn get(&self, access_token: &str, url: &str) -> Result<Response> {
+        let response = self.client
+            .get(url)
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+        check_response(response).await
+    }
+}
+
+/// Maps the statuses the sync recovers from to `CloudDriveError`
+async fn check_response(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    match status {
+        StatusCode::UNAUTHORIZED => Err(CloudDriveError::Unauthorized.into()),
+        // Graph answers an expired delta token with 410 Gone (resyncRequired)
+        StatusCode::GONE => Err(CloudDriveError::CursorExpired.into()),
+        _ => {
+            let body = response.text().await.unwrap_or_default();
+            Err(anyhow!("Microsoft Graph responded with {}: {}", status, body))
+        }
+    }
+}
+
+/// Item path below the drive root from its parent reference, e.g.
+/// `/drives/b!x/root:/Documents` + `a.pdf` -> `/Documents/a.pdf`
+fn item_path(parent_path: Option<&str>, name: &str) -> String {
+    let parent = parent_path
+        .and_then(|path| path.split_once("root:"))
+        .map(|(_, below_root)| below_root.trim_end_matches('/'))
+        .unwrap_or("");
+    let parent = urlencoding::decode(parent).map(|p| p.into_owned()).unwrap_or_else(|_| parent.to_string());
+    format!("{}/{}", parent, name)
+}
+
+#[async_trait]
+impl CloudDriveClient for OneDriveService {
+    fn provider_name(&self) -> &'static str {
+        "OneDrive"
+    }
+
+    async fn refresh_access_token(&self, refresh_token: &str) -> Result<OAuthToken> {
+        let _permit = outbound_limits::acquire(OutboundScope::OneDrive).await;
+        let token_url = format!(
+            "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+            urlencoding::encode(self.config.tenant_id.trim())
+        );
+
+        let mut form = vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", self.config.client_id.as_str()),
+            ("scope", SCOPES),
+        ];
+        if let Some(secret) = self.config.client_secret.as_deref().filter(|s| !s.is_empty()) {
+            form.push(("client_secret", secret));
+        }
+
+        let response = self.client.post(token_url).form(&form).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("OneDrive token refresh failed with {}: {}", status, body));
+        }
+
+        // Microsoft rotates refresh tokens; the new one must replace the old
+        let token: TokenResponse = response.json().await?;
+        Ok(OAuthToken::from_expires_in(token.access_token, token.expires_in, token.refresh_token))
+    }
+
+    async fn delta(&self, access_token: &str, cursor: Option<&str>) -> Result<DeltaPage> {
+        let _permit = outbound_limits::acquire(OutboundScope::OneDrive).await;
+        // The cursor is the nextLink or deltaLink URL Graph handed out
+        let url = match cursor {
+            Some(link) => link.to_string(),
+            None => format!("{}/root/delta", self.drive_url()),
+        };
+        let page: DeltaResponse = self.get(access_token, &url).await?.json().await?;
+
+        let mut files = Vec::new();
+        let mut deleted_count = 0;
+        for item in page.value {
+            if item.deleted.is_some() {
+                deleted_count += 1;
+                continue;
+            }
+            let (Some(file), Some(name)) = (item.file, item.name) else {
+                continue;
+            };
+
+            let display_path = item_path(
+                item.parent_reference.as_ref().and_then(|parent| parent.path.as_deref()),
+                &name,
+            );
+            let Some(relative_path) = relative_to_root(&display_path, &self.config.root_path) else {
+                continue;
+            };
+
+            let content_hash = file.hashes.and_then(|hashes| hashes.sha256_hash.or(hashes.quick_xor_hash));
+            files.push(CloudFile {
+                id: item.id,
+                relative_path,
+                display_path,
+                name,
+                size: item.size.unwrap_or(0),
+                modified_at: item.last_modified_date_time,
+                content_hash,
+            });
+        }
+
+        let (cursor, has_more) = match (page.next_link, page.delta_link) {
+            (Some(next_link), _) => (next_link, true),
+            (None, Some(delta_link)) => (delta_link, false),
+            (None, None) => return Err(anyhow!("Graph delta response has neither a nextLink nor a deltaLink")),
+        };
+
+        Ok(DeltaPage { files, deleted_count, cursor, has_more })
+    }
+
+    async fn download(&self, access_token: &str, file: &CloudFile) -> Result<Vec<u8>> {
+        let _permit = outbound_limits::acquire(OutboundScope::OneDrive).await;
+        let url = format!("{}/items/{}/content", self.drive_url(), urlencoding::encode(&file.id));
+        let response = self.get(access_token, &url).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn describe(&self, access_token: &str) -> Result<String> {
+        let _permit = outbound_limits::acquire(OutboundScope::OneDrive).await;
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Drive {
+            name: Option<String>,
+            drive_type: Option<String>,
+        }
+
+        let drive: Drive = self.get(access_token, &self.drive_url()).await?.json().await?;
+        let root = self.config.root_path.trim();
+        Ok(format!(
+            "Connected to {} drive '{}', syncing {}",
+            drive.drive_type.as_deref().unwrap_or("OneDrive"),
+            drive.name.as_deref().unwrap_or("unnamed"),
+            if root.is_empty() { "/" } else { root }
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_path() {
+        assert_eq!(item_path(Some("/drive/root:"), "a.pdf"), "/a.pdf");
+        assert_eq!(item_path(Some("/drive/root:/Documents/Scans"), "a.pdf"), "/Documents/Scans/a.pdf");
+        assert_eq!(item_path(Some("/drives/b!abc/root:/Shared%20Documents"), "b.pdf"), "/Shared Documents/b.pdf");
+        assert_eq!(item_path(None, "c.pdf"), "/c.pdf");
+    }
+}
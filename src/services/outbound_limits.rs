@@ -0,0 +1,220 @@
+//! Concurrency and rate limits on requests readur sends to other services, so that a
+//! self-hosted LLM is not flooded and sync servers do not lock readur out during large
+//! syncs.
+//!
+//! Every request waits for the global limits and those of its scope (`llm`, `webdav`,
+//! `onedrive`, `dropbox`). Defaults come from `OUTBOUND_MAX_CONCURRENT`,
+//! `OUTBOUND_REQUESTS_PER_MINUTE` and `OUTBOUND_BURST`, and per scope from e.g.
+//! `OUTBOUND_LLM_MAX_CONCURRENT`. Admins can replace them at runtime; instances pick
+//! up changes made on another instance within a minute.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{info, warn};
+
+use crate::db::Database;
+use crate::models::{OutboundLimit, OutboundLimitStatus, OutboundScope};
+use crate::services::rate_limit_service::{take_token, Bucket, RateLimit};
+
+/// How often limits set on other instances are picked up
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The limits of one scope in force
+struct Limiter {
+    limit: OutboundLimit,
+    slots: Option<Arc<Semaphore>>,
+    rate: Option<(RateLimit, Mutex<Bucket>)>,
+}
+
+impl Limiter {
+    fn new(limit: OutboundLimit) -> Self {
+        let slots = limit.max_concurrent.map(|n| Arc::new(Semaphore::new(n as usize)));
+        let rate = limit.requests_per_minute.map(|requests| {
+            let burst = limit.burst.unwrap_or(requests);
+            let rate = RateLimit::per_minute(requests as u32, burst as u32);
+            (rate, Mutex::new(Bucket::full(&rate, Instant::now())))
+        });
+        Limiter { limit, slots, rate }
+    }
+
+    fn in_flight(&self) -> i64 {
+        match (&self.slots, self.limit.max_concurrent) {
+            (Some(slots), Some(max)) => i64::from(max) - slots.available_permits() as i64,
+            _ => 0,
+        }
+    }
+
+    async fn wait_for_token(&self) {
+        let Some((rate, bucket)) = &self.rate else {
+            return;
+        };
+        loop {
+            let wait = {
+                let mut bucket = bucket.lock().unwrap_or_else(|e| e.into_inner());
+                match take_token(&mut bucket, rate, Instant::now()) {
+                    Ok(()) => return,
+                    Err(wait) => wait,
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        self.wait_for_token().await;
+        match &self.slots {
+            // The semaphore is never closed
+            Some(slots) => slots.clone().acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+}
+
+struct Limits {
+    /// Limits from the environment
+    defaults: HashMap<OutboundScope, OutboundLimit>,
+    /// Scopes whose limits an admin set
+    customized: HashMap<OutboundScope, OutboundLimit>,
+    limiters: HashMap<OutboundScope, Arc<Limiter>>,
+}
+
+static LIMITS: Lazy<RwLock<Limits>> = Lazy::new(|| {
+    let defaults: HashMap<OutboundScope, OutboundLimit> = OutboundScope::ALL
+        .into_iter()
+        .map(|scope| (scope, OutboundLimit::from_lookup(scope.env_prefix(), |name| std::env::var(name).ok())))
+        .collect();
+    let limiters = defaults.iter().map(|(scope, limit)| (*scope, Arc::new(Limiter::new(*limit)))).collect();
+    RwLock::new(Limits { defaults, customized: HashMap::new(), limiters })
+});
+
+fn limiter(scope: OutboundScope) -> Option<Arc<Limiter>> {
+    LIMITS.read().unwrap_or_else(|e| e.into_inner()).limiters.get(&scope).cloned()
+}
+
+/// Slots held while a request is in flight; dropping it frees them
+pub struct OutboundPermit {
+    _global: Option<OwnedSemaphorePermit>,
+    _scope: Option<OwnedSemaphorePermit>,
+}
+
+/// Wait until a request of `scope` may be sent. Hold the permit until the response,
+/// including its body, has been read.
+pub async fn acquire(scope: OutboundScope) -> OutboundPermit {
+    // The scope's slot first, so that requests queued for a busy service do not hold
+    // global slots other services could use
+    let scope_permit = match limiter(scope) {
+        Some(limiter) => limiter.acquire().await,
+        None => None,
+    };
+    let global_permit = match limiter(OutboundScope::Global) {
+        Some(limiter) => limiter.acquire().await,
+        None => None,
+    };
+    OutboundPermit { _global: global_permit, _scope: scope_permit }
+}
+
+/// Put the limits of a scope in force. Requests already waiting or in flight finish
+/// under the limits they started with.
+fn apply(scope: OutboundScope, custom: Option<OutboundLimit>) {
+    let mut limits = LIMITS.write().unwrap_or_else(|e| e.into_inner());
+    let limit = match custom {
+        Some(limit) => {
+            limits.customized.insert(scope, limit);
+            limit
+        }
+        None => {
+            limits.customized.remove(&scope);
+            limits.defaults.get(&scope).copied().unwrap_or_default()
+        }
+    };
+    let unchanged = limits.limiters.get(&scope).is_some_and(|limiter| limiter.limit == limit);
+    if !unchanged {
+        info!("Outbound limits of {}: {:?}", scope, limit);
+        limits.limiters.insert(scope, Arc::new(Limiter::new(limit)));
+    }
+}
+
+/// The limits of every scope and their load
+pub fn status() -> Vec<OutboundLimitStatus> {
+    let limits = LIMITS.read().unwrap_or_else(|e| e.into_inner());
+    OutboundScope::ALL
+        .into_iter()
+        .map(|scope| {
+            let limiter = limits.limiters.get(&scope);
+            OutboundLimitStatus {
+                scope,
+                limit: limiter.map(|limiter| limiter.limit).unwrap_or_default(),
+                customized: limits.customized.contains_key(&scope),
+                in_flight: limiter.map(|limiter| limiter.in_flight()).unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+/// Load the limits admins set, replacing those from the environment
+pub async fn load(db: &Database) -> anyhow::Result<()> {
+    let overrides = db.get_outbound_limit_overrides().await?;
+    for scope in OutboundScope::ALL {
+        let custom = overrides.iter().find(|o| o.scope == scope).map(|o| o.limit());
+        apply(scope, custom);
+    }
+    Ok(())
+}
+
+/// Set the limits of a scope on this instance; other instances follow on their next refresh
+pub async fn set(db: &Database, scope: OutboundScope, limit: OutboundLimit, updated_by: uuid::Uuid) -> anyhow::Result<()> {
+    db.set_outbound_limit_override(scope, &limit, updated_by).await?;
+    apply(scope, Some(limit));
+    Ok(())
+}
+
+/// Go back to the limits from the environment
+pub async fn reset(db: &Database, scope: OutboundScope) -> anyhow::Result<()> {
+    db.delete_outbound_limit_override(scope).await?;
+    apply(scope, None);
+    Ok(())
+}
+
+/// Keep the limits in line with those set on other instances
+pub async fn run_refresh(db: Database) {
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = load(&db).await {
+            warn!("Failed to load outbound limits: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limiter_caps_concurrency() {
+        let limiter = Limiter::new(OutboundLimit { max_concurrent: Some(2), ..Default::default() });
+        let first = limiter.acquire().await;
+        let second = limiter.acquire().await;
+        assert!(first.is_some() && second.is_some());
+        assert_eq!(limiter.in_flight(), 2);
+
+        let third = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(third.is_err());
+
+        drop(first);
+        assert_eq!(limiter.in_flight(), 1);
+        assert!(tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_limiter_does_not_wait() {
+        let limiter = Limiter::new(OutboundLimit::default());
+        for _ in 0..100 {
+            assert!(limiter.acquire().await.is_none());
+        }
+        assert_eq!(limiter.in_flight(), 0);
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::Result;
+#[cfg(feature = "ocr")]
+use tracing::{debug, info, warn};
+
+use crate::db::Database;
+use crate::models::{Document, PageArtifact};
+#[cfg(feature = "ocr")]
+use crate::models::{ArtifactKind, CreatePageArtifact};
+use crate::services::file_service::FileService;
+use crate::services::llm::llm_service::LLMService;
+
+/// Pages beyond this are not scanned for artifacts
+const DEFAULT_MAX_PAGES: usize = 20;
+/// Resolution used to rasterize PDF pages for detection
+#[cfg(feature = "ocr")]
+const RENDER_DPI: u32 = 100;
+/// Signature/figure candidates whose crop OCRs into this many words are plain text
+#[cfg(feature = "ocr")]
+const MAX_NON_TEXT_WORDS: usize = 6;
+
+/// Detects stamps, signatures and embedded figures on document pages and stores
+/// them as searchable page artifacts
+pub struct PageArtifactService {
+    db: Database,
+    file_service: FileService,
+    llm_service: LLMService,
+    max_pages: usize,
+}
+
+impl PageArtifactService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        let llm_service = LLMService::new(db.get_pool().clone());
+        let max_pages = std::env::var("PAGE_ARTIFACTS_MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_PAGES);
+
+        Self { db, file_service, llm_service, max_pages }
+    }
+
+    /// Whether artifact extraction should run automatically after OCR
+    pub fn enabled_after_ocr() -> bool {
+        std::env::var("PAGE_ARTIFACTS_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false)
+    }
+
+    /// Detect artifacts on every page of the document, replacing previous results
+    #[cfg(feature = "ocr")]
+    pub async fn extract_for_document(&self, document: &Document) -> Result<Vec<PageArtifact>> {
+        use crate::ocr::{page_artifacts, page_images};
+
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let pages = page_images::render_pages(&data, &document.mime_type, RENDER_DPI, self.max_pages).await?;
+        let describe = self.llm_service.vision_enabled();
+
+        let mut artifacts = Vec::new();
+        for (index, page) in pages.iter().enumerate() {
+            let grid = page_artifacts::compute_cell_grid(&page.to_rgb8());
+            let candidates = page_artifacts::detect_candidates(&grid);
+            debug!("Page {} of document {}: {} artifact candidates", index + 1, document.id, candidates.len());
+
+            for candidate in candidates {
+                let bbox = candidate.bbox;
+                let crop = page.crop_imm(bbox.x, bbox.y, bbox.width, bbox.height);
+
+                let ocr_text = match page_images::ocr_image(&crop, "eng").await {
+                    Ok(text) => text,
+                    Err(e) => {
+                        warn!("Region OCR failed for document {} page {}: {}", document.id, index + 1, e);
+                        String::new()
+                    }
+                };
+
+                // Large amounts of recognizable text mean this is a paragraph, not a signature or figure
+                let word_count = ocr_text.split_whitespace().filter(|w| w.chars().any(char::is_alphanumeric)).count();
+                if candidate.kind != ArtifactKind::Stamp && word_count >= MAX_NON_TEXT_WORDS {
+                    continue;
+                }
+
+                let description = if describe {
+                    match page_images::encode_png(&crop) {
+                        Ok(png) => self.llm_service
+                            .describe_image(&png, describe_prompt(candidate.kind))
+                            .await
+                            .unwrap_or_else(|e| {
+                                warn!("Vision description failed for document {}: {}", document.id, e);
+                                None
+                            }),
+                        Err(e) => {
+                            warn!("Failed to encode artifact crop: {}", e);
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+
+                artifacts.push(CreatePageArtifact {
+                    document_id: document.id,
+                    page_number: index as i32 + 1,
+                    kind: candidate.kind,
+                    bbox,
+                    detection_score: candidate.score,
+                    ocr_text: Some(ocr_text).filter(|t| !t.is_empty()),
+                    description,
+                });
+            }
+        }
+
+        let stored = self.db.replace_page_artifacts(document.id, &artifacts).await?;
+        info!("Stored {} page artifacts for document {}", stored.len(), document.id);
+        Ok(stored)
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub async fn extract_for_document(&self, _document: &Document) -> Result<Vec<PageArtifact>> {
+        anyhow::bail!("Page artifact extraction requires OCR feature")
+    }
+}
+
+#[cfg(feature = "ocr")]
+fn describe_prompt(kind: ArtifactKind) -> &'static str {
+    match kind {
+        ArtifactKind::Stamp => "This is a stamp found on a scanned document page. Transcribe any text on it and describe it in one sentence (shape, color, purpose such as received/approved/paid, dates).",
+        ArtifactKind::Signature => "This region of a scanned document may contain a handwritten signature. In one sentence, say whether it is a signature or initials and transcribe the name if legible.",
+        ArtifactKind::Figure => "This is an embedded figure from a scanned document page. Describe it in one or two sentences (chart, photo, diagram, logo) including any visible labels.",
+    }
+}
@@ -0,0 +1,260 @@
+use anyhow::{anyhow, Result};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{Document, PageTransform, RotatePagesRequest};
+use crate::ocr::page_transforms::PageTransforms;
+use crate::services::file_service::FileService;
+use crate::services::thumbnail_service::requeue_thumbnail;
+
+/// Resolution pages are rendered at to detect their rotation and skew
+#[cfg(feature = "ocr")]
+const DETECTION_DPI: u32 = 150;
+/// Resolution of generated searchable PDFs
+#[cfg(feature = "ocr")]
+const SEARCHABLE_PDF_DPI: u32 = 300;
+#[cfg(feature = "ocr")]
+const DEFAULT_PAGE_IMAGE_DPI: u32 = 150;
+#[cfg(feature = "ocr")]
+const MAX_PAGE_IMAGE_DPI: u32 = 300;
+
+/// Most pages of a PDF whose transforms are detected, from `PAGE_TRANSFORM_MAX_PAGES`
+#[cfg(feature = "ocr")]
+fn max_detected_pages() -> usize {
+    std::env::var("PAGE_TRANSFORM_MAX_PAGES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50)
+}
+
+/// Whether pages of a document type can be turned
+pub fn supports(mime_type: &str) -> bool {
+    mime_type == "application/pdf" || mime_type.starts_with("image/")
+}
+
+/// Detects and records how the scanned pages of a document are turned, and applies
+/// the recorded transforms wherever the pages are shown: OCR, page images, thumbnails
+/// and searchable PDFs. Pages rotated by hand go through the same transforms.
+pub struct PageTransformService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl PageTransformService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Transforms to OCR a document with. With `detect`, scanned pages without a
+    /// recorded transform are detected and recorded first, from `plaintext_path`, the
+    /// decrypted copy of the document's file at `file_path`.
+    pub async fn prepare(&self, document_id: Uuid, file_path: &str, plaintext_path: &str, mime_type: &str, detect: bool) -> PageTransforms {
+        #[cfg(feature = "ocr")]
+        if detect && supports(mime_type) {
+            if let Err(e) = self.detect(document_id, file_path, plaintext_path, mime_type).await {
+                warn!("Failed to detect the page transforms of document {}: {}", document_id, e);
+            }
+        }
+        #[cfg(not(feature = "ocr"))]
+        let _ = (file_path, plaintext_path, mime_type, detect);
+
+        match self.db.get_page_transforms(document_id).await {
+            Ok(transforms) => PageTransforms::new(transforms),
+            Err(e) => {
+                warn!("Failed to load the page transforms of document {}: {}", document_id, e);
+                PageTransforms::default()
+            }
+        }
+    }
+
+    #[cfg(feature = "ocr")]
+    async fn detect(&self, document_id: Uuid, file_path: &str, path: &str, mime_type: &str) -> Result<()> {
+        use crate::ocr::{page_images, page_transforms, pdf_pages};
+
+        let recorded: std::collections::HashSet<i32> = self
+            .db
+            .get_page_transforms(document_id)
+            .await?
+            .into_iter()
+            .map(|t| t.page_number)
+            .collect();
+
+        // Pages with a text layer are laid out by their producer and left as they are
+        let pages: Vec<u32> = if mime_type == "application/pdf" {
+            let texts = pdf_pages::page_texts(std::path::Path::new(path)).await?;
+            pdf_pages::pages_without_text(&texts, 3)
+        } else {
+            vec![1]
+        };
+        let pages: Vec<u32> = pages
+            .into_iter()
+            .filter(|page| !recorded.contains(&(*page as i32)))
+            .take(max_detected_pages())
+            .collect();
+        if pages.is_empty() {
+            return Ok(());
+        }
+
+        let data = tokio::fs::read(path).await?;
+        let mut detected = Vec::with_capacity(pages.len());
+        for page in pages {
+            let rendered = page_images::render_page_range(&data, mime_type, DETECTION_DPI, page, page).await?;
+            let Some(image) = rendered.first() else {
+                continue;
+            };
+            let found = page_transforms::detect(image).await?;
+            detected.push(PageTransform {
+                rotation: found.rotation,
+                skew_degrees: found.skew_degrees,
+                confidence: found.confidence,
+                ..PageTransform::identity(document_id, page as i32)
+            });
+        }
+
+        let turned = detected.iter().filter(|t| !t.is_identity()).count();
+        self.db.record_detected_page_transforms(&detected).await?;
+        if turned > 0 {
+            info!("Detected turned pages in document {}: {} of {} checked", document_id, turned, detected.len());
+            // The thumbnail shows the first page upright from now on
+            if detected.iter().any(|t| t.page_number == 1 && !t.is_identity()) {
+                self.file_service.invalidate_thumbnail(file_path).await;
+                requeue_thumbnail(&self.db, document_id).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn page_count(&self, document: &Document) -> Result<u32> {
+        if document.mime_type != "application/pdf" {
+            return Ok(1);
+        }
+        let data = self.file_service.read_file(&document.file_path).await?;
+        crate::ocr::pdf_pages::TempPdf::from_bytes(&data).await?.page_count().await
+    }
+
+    /// Turn pages of a document by hand. The stored file is unchanged; the document's
+    /// thumbnail is regenerated, and the caller queues it for OCR again.
+    pub async fn rotate(&self, document: &Document, request: &RotatePagesRequest, user_id: Uuid) -> Result<Vec<PageTransform>> {
+        if !supports(&document.mime_type) {
+            return Err(anyhow!("Only PDF and image pages can be rotated"));
+        }
+        let page_count = self.page_count(document).await?;
+        let pages = request.pages(page_count).map_err(|e| anyhow!(e))?;
+
+        let current = self.db.get_page_transforms(document.id).await?;
+        let transforms: Vec<PageTransform> = pages
+            .iter()
+            .map(|&page| {
+                current
+                    .iter()
+                    .find(|t| t.page_number == page as i32)
+                    .cloned()
+                    .unwrap_or_else(|| PageTransform::identity(document.id, page as i32))
+                    .rotated_by(request.rotation, request.reset_skew, user_id)
+            })
+            .collect();
+        self.db.set_page_transforms(&transforms).await?;
+
+        self.refresh_thumbnail(document).await;
+        info!("Rotated {} pages of document {} by {} degrees", transforms.len(), document.id, request.rotation);
+        Ok(transforms)
+    }
+
+    /// Forget all transforms of a document, detected or set by hand. Detection runs
+    /// again the next time the document is OCRed.
+    pub async fn reset(&self, document: &Document) -> Result<u64> {
+        let deleted = self.db.delete_page_transforms(document.id).await?;
+        self.refresh_thumbnail(document).await;
+        Ok(deleted)
+    }
+
+    async fn refresh_thumbnail(&self, document: &Document) {
+        self.file_service.invalidate_thumbnail(&document.file_path).await;
+        requeue_thumbnail(&self.db, document.id).await;
+    }
+
+    /// A page of a document as a PNG, turned upright
+    #[cfg(feature = "ocr")]
+    pub async fn render_page(&self, document: &Document, page: u32, dpi: Option<u32>) -> Result<Vec<u8>> {
+        use crate::ocr::{page_images, page_transforms};
+
+        let dpi = dpi.unwrap_or(DEFAULT_PAGE_IMAGE_DPI).clamp(36, MAX_PAGE_IMAGE_DPI);
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let image = page_images::render_page_range(&data, &document.mime_type, dpi, page, page)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Page {} could not be rendered", page))?;
+
+        let transforms = PageTransforms::new(self.db.get_page_transforms(document.id).await?);
+        let image = match transforms.get(page as i32) {
+            Some(transform) => page_transforms::apply_transform(&image, transform),
+            None => image,
+        };
+        page_images::encode_png(&image)
+    }
+
+    /// A PDF of the document's pages, turned upright, with an OCR text layer in `language`
+    #[cfg(feature = "ocr")]
+    pub async fn searchable_pdf(&self, document: &Document, language: &str) -> Result<Vec<u8>> {
+        use crate::ocr::{page_images, page_transforms};
+
+        if !supports(&document.mime_type) {
+            return Err(anyhow!("Only PDF and image documents can be made searchable"));
+        }
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let page_count = self.page_count(document).await?;
+        let transforms = PageTransforms::new(self.db.get_page_transforms(document.id).await?);
+
+        let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+        let work_dir = std::path::PathBuf::from(temp_dir).join(format!("searchable_{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&work_dir).await?;
+
+        let result: Result<Vec<u8>> = async {
+            // One page at a time, so long documents do not have to fit in memory
+            let mut page_files = Vec::with_capacity(page_count as usize);
+            for page in 1..=page_count {
+                let image = page_images::render_page_range(&data, &document.mime_type, SEARCHABLE_PDF_DPI, page, page)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow!("Page {} could not be rendered", page))?;
+                let image = match transforms.get(page as i32) {
+                    Some(transform) => page_transforms::apply_transform(&image, transform),
+                    None => image,
+                };
+
+                let page_file = work_dir.join(format!("page-{:05}.png", page));
+                image.save_with_format(&page_file, image::ImageFormat::Png)?;
+                page_files.push(page_file);
+            }
+            page_images::images_to_pdf(&page_files, language).await
+        }
+        .await;
+        let _ = tokio::fs::remove_dir_all(&work_dir).await;
+        result
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub async fn render_page(&self, _document: &Document, _page: u32, _dpi: Option<u32>) -> Result<Vec<u8>> {
+        anyhow::bail!("Page images require OCR feature")
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub async fn searchable_pdf(&self, _document: &Document, _language: &str) -> Result<Vec<u8>> {
+        anyhow::bail!("Searchable PDFs require OCR feature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supports() {
+        assert!(supports("application/pdf"));
+        assert!(supports("image/tiff"));
+        assert!(!supports("text/plain"));
+    }
+}
@@ -0,0 +1,86 @@
+//! WebAuthn passkeys for local accounts. Passkeys are enabled by setting
+//! `WEBAUTHN_RP_ORIGIN` to the URL users open Readur at, e.g.
+//! `https://readur.example.com`. `WEBAUTHN_RP_ID` defaults to that URL's host and
+//! only needs setting to share passkeys with a parent domain.
+
+use once_cell::sync::Lazy;
+use tracing::{info, warn};
+use webauthn_rs::prelude::Url;
+use webauthn_rs::{Webauthn, WebauthnBuilder};
+
+pub const RP_NAME: &str = "Readur";
+pub const CEREMONY_REGISTRATION: &str = "registration";
+pub const CEREMONY_AUTHENTICATION: &str = "authentication";
+/// Minutes the browser has to answer a challenge
+pub const CEREMONY_TIMEOUT_MINUTES: i32 = 5;
+pub const MAX_PASSKEYS_PER_USER: usize = 20;
+pub const MAX_NAME_LENGTH: usize = 100;
+
+static WEBAUTHN: Lazy<Option<Webauthn>> = Lazy::new(|| {
+    let origin = std::env::var("WEBAUTHN_RP_ORIGIN").ok()?;
+    let rp_id = std::env::var("WEBAUTHN_RP_ID").ok();
+    let Some((rp_id, origin)) = relying_party(&origin, rp_id.as_deref()) else {
+        warn!("Passkeys disabled: WEBAUTHN_RP_ORIGIN '{}' is not a valid URL with a host", origin);
+        return None;
+    };
+
+    match WebauthnBuilder::new(&rp_id, &origin).and_then(|builder| builder.rp_name(RP_NAME).build()) {
+        Ok(webauthn) => {
+            info!("Passkeys enabled for relying party '{}' at {}", rp_id, origin);
+            Some(webauthn)
+        }
+        Err(e) => {
+            warn!("Passkeys disabled: invalid relying party '{}' for {}: {}", rp_id, origin, e);
+            None
+        }
+    }
+});
+
+/// The configured relying party, or None when passkeys are not set up
+pub fn webauthn() -> Option<&'static Webauthn> {
+    WEBAUTHN.as_ref()
+}
+
+/// Relying party id and origin from the configured values. The id defaults to the
+/// origin's host.
+pub fn relying_party(origin: &str, rp_id: Option<&str>) -> Option<(String, Url)> {
+    let origin = Url::parse(origin.trim()).ok()?;
+    let rp_id = match rp_id.map(str::trim).filter(|id| !id.is_empty()) {
+        Some(id) => id.to_string(),
+        None => origin.host_str()?.to_string(),
+    };
+    Some((rp_id, origin))
+}
+
+/// A trimmed passkey name, or None when it is empty or too long
+pub fn normalize_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relying_party_defaults_to_origin_host() {
+        let (rp_id, origin) = relying_party("https://readur.example.com", None).unwrap();
+        assert_eq!(rp_id, "readur.example.com");
+        assert_eq!(origin.as_str(), "https://readur.example.com/");
+
+        let (rp_id, _) = relying_party("https://readur.example.com:8443", Some(" example.com ")).unwrap();
+        assert_eq!(rp_id, "example.com");
+
+        assert!(relying_party("not a url", None).is_none());
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("  Work laptop "), Some("Work laptop".to_string()));
+        assert_eq!(normalize_name("   "), None);
+        assert_eq!(normalize_name(&"x".repeat(MAX_NAME_LENGTH + 1)), None);
+    }
+}
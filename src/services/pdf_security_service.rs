@@ -0,0 +1,425 @@
+//! Content security scanning of PDFs: embedded JavaScript, launch actions, embedded
+//! files and similar active content, which a viewer may run or open for whoever
+//! downloads the document.
+//!
+//! PDFs are scanned when they are ingested and the findings are kept in the document's
+//! metadata. With `PDF_SECURITY_STRIP` the active content is stripped by rewriting the
+//! file with Ghostscript in the converter sandbox; the rewritten copy becomes the
+//! document's contents and the original is kept as its first version. Downloads of an
+//! original with active content in it carry a warning header.
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{
+    Document, PdfSecurityFinding, PdfSecurityFindingKind, PdfSecurityReport, PDF_SECURITY_METADATA_KEY,
+};
+use crate::services::converter_sandbox::{self, SandboxedCommand};
+use crate::services::document_version_service::DocumentVersionService;
+use crate::services::file_service::FileService;
+
+/// PDFs are scanned unless `PDF_SECURITY_SCAN` is false
+static ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("PDF_SECURITY_SCAN")
+        .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+        .unwrap_or(true)
+});
+
+/// Active content is stripped at ingestion when `PDF_SECURITY_STRIP` is true
+static STRIP: Lazy<bool> = Lazy::new(|| {
+    std::env::var("PDF_SECURITY_STRIP")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+});
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 120;
+
+/// Response header naming the active content of an unsanitized download
+pub const SECURITY_WARNING_HEADER: &str = "X-Readur-Security-Warning";
+
+/// Why signed PDFs are left as they are at ingestion
+const SIGNED_PDF_NOT_STRIPPED: &str = "Stripping active content would break the PDF's signatures";
+
+pub fn enabled() -> bool {
+    *ENABLED
+}
+
+pub fn strip_enabled() -> bool {
+    *STRIP
+}
+
+/// The kind of active content a PDF name marks, if any. Both `/JS` and `/JavaScript`
+/// appear with scripts; `/EF` holds the contents of an embedded file.
+fn finding_kind(name: &[u8]) -> Option<PdfSecurityFindingKind> {
+    match name {
+        b"JS" | b"JavaScript" => Some(PdfSecurityFindingKind::Javascript),
+        b"Launch" => Some(PdfSecurityFindingKind::LaunchAction),
+        b"EmbeddedFiles" | b"EF" => Some(PdfSecurityFindingKind::EmbeddedFile),
+        b"FileAttachment" => Some(PdfSecurityFindingKind::FileAttachment),
+        b"SubmitForm" => Some(PdfSecurityFindingKind::SubmitForm),
+        b"RichMedia" => Some(PdfSecurityFindingKind::RichMedia),
+        b"XFA" => Some(PdfSecurityFindingKind::Xfa),
+        _ => None,
+    }
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(byte, 0 | b'\t' | b'\n' | 0x0c | b'\r' | b' ') || b"()<>[]{}/%".contains(&byte)
+}
+
+/// A name with its `#xx` escapes decoded, which hide names such as `/J#61vaScript`
+/// from plain byte searches
+fn decode_name(raw: &[u8]) -> Vec<u8> {
+    let mut name = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let escaped = raw[i] == b'#'
+            && i + 2 < raw.len()
+            && raw[i + 1].is_ascii_hexdigit()
+            && raw[i + 2].is_ascii_hexdigit();
+        if escaped {
+            name.push((hex_value(raw[i + 1]) << 4) | hex_value(raw[i + 2]));
+            i += 3;
+        } else {
+            name.push(raw[i]);
+            i += 1;
+        }
+    }
+    name
+}
+
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+/// The active content named in the file's uncompressed objects, and whether it keeps
+/// further objects in compressed object streams, which this scan cannot see into
+pub fn scan_names(data: &[u8]) -> (Vec<PdfSecurityFinding>, bool) {
+    let mut counts: BTreeMap<PdfSecurityFindingKind, u32> = BTreeMap::new();
+    let mut compressed_objects = false;
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] != b'/' {
+            i += 1;
+            continue;
+        }
+        let start = i + 1;
+        let mut end = start;
+        while end < data.len() && !is_delimiter(data[end]) {
+            end += 1;
+        }
+        let name = decode_name(&data[start..end]);
+        if name == b"ObjStm" {
+            compressed_objects = true;
+        } else if let Some(kind) = finding_kind(&name) {
+            *counts.entry(kind).or_insert(0) += 1;
+        }
+        i = end;
+    }
+    let findings = counts
+        .into_iter()
+        .map(|(kind, occurrences)| PdfSecurityFinding { kind, occurrences })
+        .collect();
+    (findings, compressed_objects)
+}
+
+/// Scan a PDF for active content. Embedded files in compressed object streams are
+/// listed with poppler's `pdfdetach`; `PDFDETACH_PATH` overrides the binary.
+pub async fn scan(data: &[u8]) -> PdfSecurityReport {
+    let (mut findings, compressed_objects) = scan_names(data);
+    let has_embedded_files = findings.iter().any(|finding| finding.kind == PdfSecurityFindingKind::EmbeddedFile);
+    if compressed_objects && !has_embedded_files {
+        match count_embedded_files(data).await {
+            Ok(0) => {}
+            Ok(occurrences) => {
+                findings.push(PdfSecurityFinding { kind: PdfSecurityFindingKind::EmbeddedFile, occurrences });
+                findings.sort_by_key(|finding| finding.kind);
+            }
+            Err(e) => warn!("Failed to list the embedded files of a PDF: {}", e),
+        }
+    }
+    PdfSecurityReport::scanned(findings, compressed_objects, Utc::now())
+}
+
+async fn count_embedded_files(data: &[u8]) -> Result<u32> {
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let input = PathBuf::from(temp_dir).join(format!("pdfdetach_{}.pdf", Uuid::new_v4()));
+    tokio::fs::write(&input, data).await?;
+
+    let binary = std::env::var("PDFDETACH_PATH").unwrap_or_else(|_| "pdfdetach".to_string());
+    let result = SandboxedCommand::new(&binary)
+        .arg("-list")
+        .arg(&input)
+        .input(data)
+        .timeout(Duration::from_secs(timeout_seconds()))
+        .output()
+        .await;
+    let _ = tokio::fs::remove_file(&input).await;
+
+    let output = result?;
+    if !output.status.success() {
+        return Err(anyhow!("{} failed: {}", binary, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    parse_pdfdetach_count(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow!("Unexpected output from {}", binary))
+}
+
+/// The count on the first line of `pdfdetach -list`, e.g. `2 embedded files`
+fn parse_pdfdetach_count(output: &str) -> Option<u32> {
+    output.lines().next()?.split_whitespace().next()?.parse().ok()
+}
+
+fn timeout_seconds() -> u64 {
+    std::env::var("PDF_SECURITY_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_TIMEOUT_SECONDS)
+}
+
+/// A copy of the PDF without its active content. The file is rewritten keeping its
+/// annotations first, and without them when scripts or attachments survive in them;
+/// a copy is only returned when a scan finds nothing left.
+pub async fn sanitize(data: &[u8]) -> Result<Vec<u8>> {
+    for keep_annotations in [true, false] {
+        let sanitized = rewrite(data, keep_annotations).await?;
+        let report = scan(&sanitized).await;
+        if !report.has_findings() {
+            return Ok(sanitized);
+        }
+        debug!("Rewritten PDF still has {}", report.kinds());
+    }
+    Err(anyhow!("Active content was left after rewriting the PDF"))
+}
+
+/// Rewrite a PDF with Ghostscript's pdfwrite, which leaves scripts, actions and
+/// embedded files out. `GHOSTSCRIPT_PATH` overrides the `gs` binary and
+/// `PDF_SECURITY_TIMEOUT_SECONDS` bounds the run.
+async fn rewrite(data: &[u8], keep_annotations: bool) -> Result<Vec<u8>> {
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let work_dir = PathBuf::from(temp_dir).join(format!("pdf_sanitize_{}", Uuid::new_v4()));
+    tokio::fs::create_dir_all(&work_dir).await?;
+
+    let result = rewrite_in(&work_dir, data, keep_annotations).await;
+    let _ = tokio::fs::remove_dir_all(&work_dir).await;
+    result
+}
+
+async fn rewrite_in(work_dir: &Path, data: &[u8], keep_annotations: bool) -> Result<Vec<u8>> {
+    let input = work_dir.join("input.pdf");
+    let output_path = work_dir.join("output.pdf");
+    tokio::fs::write(&input, data).await?;
+
+    let binary = std::env::var("GHOSTSCRIPT_PATH").unwrap_or_else(|_| "gs".to_string());
+    // Objects are written uncompressed so the scan of the result sees all of them
+    let output = SandboxedCommand::new(&binary)
+        .args(["-q", "-dBATCH", "-dNOPAUSE", "-dSAFER", "-sDEVICE=pdfwrite"])
+        .args(["-dPreserveEmbeddedFiles=false", "-dWriteObjStms=false", "-dWriteXRefStm=false"])
+        .arg(format!("-dPreserveAnnots={}", keep_annotations))
+        .arg(format!("-sOutputFile={}", output_path.display()))
+        .arg(&input)
+        .current_dir(work_dir)
+        .input(data)
+        .timeout(Duration::from_secs(timeout_seconds()))
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Rewriting the PDF failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    tokio::fs::read(&output_path)
+        .await
+        .map_err(|e| anyhow!("Rewriting produced no PDF: {}", e))
+}
+
+/// What the scan of a PDF being ingested found
+pub struct IngestionScan {
+    pub report: PdfSecurityReport,
+    /// The copy to make the document's contents, when active content was stripped
+    pub sanitized: Option<Vec<u8>>,
+}
+
+/// Scan a PDF being ingested and, with stripping on, strip what was found. None for
+/// PDFs without active content. Signed PDFs are not stripped.
+pub async fn ingestion_scan(data: &[u8], file_hash: &str, signed: bool) -> Option<IngestionScan> {
+    if !enabled() {
+        return None;
+    }
+    let report = scan(data).await;
+    if !report.has_findings() {
+        return None;
+    }
+    let report = PdfSecurityReport { file_hash: Some(file_hash.to_string()), ..report };
+    if !strip_enabled() {
+        return Some(IngestionScan { report, sanitized: None });
+    }
+    if signed {
+        let report = PdfSecurityReport { sanitize_error: Some(SIGNED_PDF_NOT_STRIPPED.to_string()), ..report };
+        return Some(IngestionScan { report, sanitized: None });
+    }
+
+    match sanitize(data).await {
+        Ok(sanitized) => {
+            let report = PdfSecurityReport {
+                sanitized_file_hash: Some(converter_sandbox::hash_data(&sanitized)),
+                sanitized_at: Some(Utc::now()),
+                ..report
+            };
+            Some(IngestionScan { report, sanitized: Some(sanitized) })
+        }
+        Err(e) => {
+            warn!("Failed to strip the active content of a PDF: {}", e);
+            let report = PdfSecurityReport { sanitize_error: Some(e.to_string()), ..report };
+            Some(IngestionScan { report, sanitized: None })
+        }
+    }
+}
+
+/// The security report stored with a document, if active content was found in it
+pub fn stored_report(document: &Document) -> Option<PdfSecurityReport> {
+    let value = document.source_metadata.as_ref()?.get(PDF_SECURITY_METADATA_KEY)?;
+    serde_json::from_value(value.clone()).ok()
+}
+
+/// The warning to send with a download of the document, when its contents are an
+/// original with active content in it
+pub fn download_warning(document: &Document) -> Option<String> {
+    let report = stored_report(document)?;
+    report
+        .is_unsanitized(document.file_hash.as_deref())
+        .then(|| format!("unsanitized-active-content; kinds=\"{}\"", report.kinds()))
+}
+
+/// Strips the active content of stored documents
+pub struct PdfSecurityService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl PdfSecurityService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Make `sanitized` the contents of a document that was just ingested, keeping the
+    /// original as its first version. When that fails the original stays and the
+    /// report says why.
+    pub async fn keep_sanitized(&self, document: Document, sanitized: &[u8], report: PdfSecurityReport) -> Document {
+        let service = DocumentVersionService::new(self.db.clone(), self.file_service.clone());
+        match service.add_version(&document, sanitized, document.original_modified_at).await {
+            Ok(updated) => {
+                info!("Stripped the active content of document {}", document.id);
+                updated
+            }
+            Err(e) => {
+                warn!("Failed to store the sanitized copy of document {}: {}", document.id, e);
+                let report = PdfSecurityReport {
+                    sanitized_file_hash: None,
+                    sanitized_at: None,
+                    sanitize_error: Some(e.to_string()),
+                    ..report
+                };
+                if let Err(e) = self.store_report(document.id, &report).await {
+                    warn!("Failed to record the security report of document {}: {}", document.id, e);
+                }
+                document
+            }
+        }
+    }
+
+    /// Scan the document's current contents and strip what is found, keeping the
+    /// current contents as a version. Returns the document, changed only when there was
+    /// something to strip, and the report.
+    pub async fn sanitize_document(&self, document: &Document) -> Result<(Document, PdfSecurityReport)> {
+        if document.mime_type != "application/pdf" {
+            return Err(anyhow!("Document {} is not a PDF", document.id));
+        }
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let report = PdfSecurityReport { file_hash: document.file_hash.clone(), ..scan(&data).await };
+        if !report.has_findings() {
+            return Ok((document.clone(), report));
+        }
+
+        let sanitized = sanitize(&data).await?;
+        let report = PdfSecurityReport {
+            sanitized_file_hash: Some(converter_sandbox::hash_data(&sanitized)),
+            sanitized_at: Some(Utc::now()),
+            ..report
+        };
+        let updated = DocumentVersionService::new(self.db.clone(), self.file_service.clone())
+            .add_version(document, &sanitized, document.original_modified_at)
+            .await?;
+        self.store_report(document.id, &report).await?;
+        info!("Stripped the active content of document {}", document.id);
+        Ok((updated, report))
+    }
+
+    async fn store_report(&self, document_id: Uuid, report: &PdfSecurityReport) -> Result<()> {
+        self.db
+            .set_document_metadata_value(document_id, PDF_SECURITY_METADATA_KEY, serde_json::to_value(report)?)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(data: &[u8]) -> Vec<PdfSecurityFindingKind> {
+        scan_names(data).0.into_iter().map(|finding| finding.kind).collect()
+    }
+
+    #[test]
+    fn test_scan_names() {
+        let pdf = b"1 0 obj\n<< /Type /Catalog /OpenAction << /S /JavaScript /JS (app.alert\\(1\\)) >> >>\nendobj\n\
+            2 0 obj\n<</S/Launch/F(cmd.exe)>>\nendobj\n3 0 obj\n<< /Names << /EmbeddedFiles 4 0 R >> >>\nendobj";
+        let (findings, compressed_objects) = scan_names(pdf);
+        assert!(!compressed_objects);
+        assert_eq!(findings[0], PdfSecurityFinding { kind: PdfSecurityFindingKind::Javascript, occurrences: 2 });
+        assert_eq!(
+            kinds(pdf),
+            vec![
+                PdfSecurityFindingKind::Javascript,
+                PdfSecurityFindingKind::LaunchAction,
+                PdfSecurityFindingKind::EmbeddedFile
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_names_matches_whole_names() {
+        assert!(kinds(b"<< /JSON 1 /Launcher 2 /EFX 3 /Type /Page >>").is_empty());
+        assert_eq!(kinds(b"/S /J#61vaScript"), vec![PdfSecurityFindingKind::Javascript]);
+        assert_eq!(kinds(b"/Subtype/FileAttachment"), vec![PdfSecurityFindingKind::FileAttachment]);
+        assert!(scan_names(b"<< /Type /ObjStm /N 12 >>").1);
+    }
+
+    #[test]
+    fn test_decode_name() {
+        assert_eq!(decode_name(b"A#20B"), b"A B");
+        assert_eq!(decode_name(b"A#2"), b"A#2");
+        assert_eq!(decode_name(b"A#zzB"), b"A#zzB");
+    }
+
+    #[test]
+    fn test_parse_pdfdetach_count() {
+        assert_eq!(parse_pdfdetach_count("2 embedded files\n1: invoice.xml\n2: run.exe\n"), Some(2));
+        assert_eq!(parse_pdfdetach_count("0 embedded files\n"), Some(0));
+        assert_eq!(parse_pdfdetach_count(""), None);
+    }
+}
@@ -0,0 +1,296 @@
+//! Verification of the digital signatures embedded in PDFs, with poppler's `pdfsig`.
+//!
+//! Signatures are checked when a PDF is ingested and again whenever its contents are
+//! replaced (by a redaction, a page edit or a new version), so that a document whose
+//! signatures no longer hold up after a change in Readur is flagged as broken.
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{
+    CreateNotification, Document, PdfSignature, PdfSignatureReport, PdfSignatureStatus, PDF_SIGNATURES_METADATA_KEY,
+};
+use crate::services::converter_sandbox::SandboxedCommand;
+use crate::services::file_service::FileService;
+use crate::services::i18n;
+
+/// Signatures are checked unless `PDF_SIGNATURE_VERIFICATION` is false
+static ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("PDF_SIGNATURE_VERIFICATION")
+        .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+        .unwrap_or(true)
+});
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 30;
+
+/// Every signed PDF has a byte range naming the parts of the file the signature covers
+const BYTE_RANGE_MARKER: &[u8] = b"/ByteRange";
+
+pub fn enabled() -> bool {
+    *ENABLED
+}
+
+/// Whether the file may hold signatures, to skip running `pdfsig` on the many PDFs that don't
+pub fn might_be_signed(data: &[u8]) -> bool {
+    data.windows(BYTE_RANGE_MARKER.len()).any(|window| window == BYTE_RANGE_MARKER)
+}
+
+/// The signature report to store with a PDF being ingested, or None for unsigned PDFs
+pub async fn ingestion_report(data: &[u8], file_hash: &str) -> Option<serde_json::Value> {
+    if !enabled() || !might_be_signed(data) {
+        return None;
+    }
+    let report = match verify(data).await {
+        Ok(signatures) => PdfSignatureReport::checked(signatures, Utc::now()),
+        Err(e) => {
+            warn!("Failed to verify PDF signatures: {}", e);
+            PdfSignatureReport::unverified(e.to_string(), Utc::now())
+        }
+    };
+    if report.status == PdfSignatureStatus::Unsigned {
+        return None;
+    }
+    serde_json::to_value(PdfSignatureReport { file_hash: Some(file_hash.to_string()), ..report }).ok()
+}
+
+/// Check the signatures of a PDF. `PDFSIG_PATH` overrides the `pdfsig` binary,
+/// `PDF_SIGNATURE_NSS_DIR` names the NSS database of trusted certificates and
+/// `PDF_SIGNATURE_TIMEOUT_SECONDS` bounds the check.
+pub async fn verify(data: &[u8]) -> Result<Vec<PdfSignature>> {
+    if !might_be_signed(data) {
+        return Ok(Vec::new());
+    }
+
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let input = PathBuf::from(temp_dir).join(format!("pdfsig_{}.pdf", Uuid::new_v4()));
+    tokio::fs::write(&input, data).await?;
+    let result = run_pdfsig(&input, data).await;
+    let _ = tokio::fs::remove_file(&input).await;
+    result
+}
+
+async fn run_pdfsig(input: &std::path::Path, data: &[u8]) -> Result<Vec<PdfSignature>> {
+    let binary = std::env::var("PDFSIG_PATH").unwrap_or_else(|_| "pdfsig".to_string());
+    let timeout = std::env::var("PDF_SIGNATURE_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECONDS);
+
+    // Ingestion should not wait on certificate authorities' OCSP responders
+    let mut command = SandboxedCommand::new(&binary).arg("-no-ocsp");
+    if let Ok(nss_dir) = std::env::var("PDF_SIGNATURE_NSS_DIR") {
+        command = command.arg("-nssdir").arg(nss_dir);
+    }
+    let output = command
+        .arg(input)
+        .input(data)
+        .timeout(Duration::from_secs(timeout))
+        .output()
+        .await?;
+
+    // pdfsig exits with an error both for unsigned files and for invalid signatures,
+    // so what it printed decides
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let signatures = parse_pdfsig_output(&stdout);
+    if signatures.is_empty() && !output.status.success() && !stdout.contains("does not contain any signatures") {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("pdfsig failed: {}", stderr.trim()));
+    }
+    Ok(signatures)
+}
+
+/// The signatures described in `pdfsig` output, in the order they appear in the file
+pub fn parse_pdfsig_output(output: &str) -> Vec<PdfSignature> {
+    let mut signatures = Vec::new();
+    let mut current: Option<PdfSignature> = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+        if line.starts_with("Signature #") {
+            signatures.extend(current.take());
+            current = Some(PdfSignature {
+                signer_name: None,
+                signer_dn: None,
+                signing_time: None,
+                hash_algorithm: None,
+                signature_type: None,
+                signature_valid: false,
+                signature_status: "Signature has not been verified".to_string(),
+                certificate_trusted: false,
+                certificate_status: None,
+                covers_whole_document: false,
+            });
+            continue;
+        }
+        let (Some(signature), Some(item)) = (current.as_mut(), line.strip_prefix("- ")) else {
+            continue;
+        };
+
+        match item.split_once(": ") {
+            Some((key, value)) => {
+                let value = value.trim().to_string();
+                match key {
+                    "Signer Certificate Common Name" => signature.signer_name = Some(value),
+                    "Signer full Distinguished Name" => signature.signer_dn = Some(value),
+                    "Signing Time" => signature.signing_time = Some(value),
+                    "Signing Hash Algorithm" => signature.hash_algorithm = Some(value),
+                    "Signature Type" => signature.signature_type = Some(value),
+                    "Signature Validation" => {
+                        signature.signature_valid = value == "Signature is Valid.";
+                        signature.signature_status = value;
+                    }
+                    "Certificate Validation" => {
+                        signature.certificate_trusted = value == "Certificate is Trusted.";
+                        signature.certificate_status = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+            None if item == "Total document signed" => signature.covers_whole_document = true,
+            None => {}
+        }
+    }
+
+    signatures.extend(current);
+    signatures
+}
+
+/// The signature report stored with a document, if it was ever checked
+pub fn stored_report(document: &Document) -> Option<PdfSignatureReport> {
+    let value = document.source_metadata.as_ref()?.get(PDF_SIGNATURES_METADATA_KEY)?;
+    serde_json::from_value(value.clone()).ok()
+}
+
+/// Checks the signatures of stored documents and records the result
+pub struct PdfSignatureService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl PdfSignatureService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Check the document's current contents, comparing with the last report, and
+    /// store the result. The owner is notified when a change broke the signatures.
+    pub async fn recheck(&self, document: &Document) -> Result<PdfSignatureReport> {
+        if document.mime_type != "application/pdf" {
+            return Err(anyhow!("Document {} is not a PDF", document.id));
+        }
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let previous = stored_report(document);
+        if previous.is_none() && !might_be_signed(&data) {
+            return Ok(PdfSignatureReport::checked(Vec::new(), Utc::now()));
+        }
+
+        let now = Utc::now();
+        let report = match verify(&data).await {
+            Ok(signatures) => PdfSignatureReport::checked(signatures, now),
+            Err(e) => PdfSignatureReport::unverified(e.to_string(), now),
+        };
+        let report = PdfSignatureReport { file_hash: document.file_hash.clone(), ..report };
+        let report = match &previous {
+            Some(previous) => report.after_change(previous, now),
+            None => report,
+        };
+
+        self.db
+            .set_document_metadata_value(document.id, PDF_SIGNATURES_METADATA_KEY, serde_json::to_value(&report)?)
+            .await?;
+
+        let newly_broken = report.status == PdfSignatureStatus::Broken
+            && previous.is_some_and(|previous| previous.status != PdfSignatureStatus::Broken);
+        if newly_broken {
+            info!("The signatures of document {} no longer hold up after a change", document.id);
+            self.notify_broken(document).await;
+        }
+        Ok(report)
+    }
+
+    /// Check a document whose contents may have changed, after it was processed again.
+    /// Files that were checked as they are now are left alone.
+    pub async fn recheck_after_ocr(&self, document: &Document) -> Result<()> {
+        if !enabled() || document.mime_type != "application/pdf" {
+            return Ok(());
+        }
+        let unchanged = stored_report(document)
+            .is_some_and(|report| report.file_hash.is_some() && report.file_hash == document.file_hash);
+        if unchanged {
+            return Ok(());
+        }
+        self.recheck(document).await?;
+        Ok(())
+    }
+
+    async fn notify_broken(&self, document: &Document) {
+        let locale = i18n::locale_of(&self.db, document.user_id).await;
+        let notification = CreateNotification {
+            action_url: Some(format!("/documents/{}", document.id)),
+            metadata: Some(serde_json::json!({ "document_id": document.id, "kind": "pdf_signature_broken" })),
+            ..i18n::notification(
+                &locale,
+                "warning",
+                "notification.signature_broken",
+                &[("filename", document.original_filename.clone())],
+            )
+        };
+        if let Err(e) = self.db.create_notification(document.user_id, &notification).await {
+            warn!("Failed to notify about the broken signatures of document {}: {}", document.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pdfsig_output() {
+        let output = "Digital Signature Info of: contract.pdf\n\
+            Signature #1:\n  \
+              - Signature Field Name: Signature1\n  \
+              - Signer Certificate Common Name: Jane Doe\n  \
+              - Signer full Distinguished Name: CN=Jane Doe,O=ACME Corp,C=US\n  \
+              - Signing Time: Jan 15 2024 10:00:00\n  \
+              - Signing Hash Algorithm: SHA-256\n  \
+              - Signature Type: adbe.pkcs7.detached\n  \
+              - Signed Ranges: [0 - 1234], [5678 - 9999]\n  \
+              - Not total document signed\n  \
+              - Signature Validation: Signature is Valid.\n  \
+              - Certificate Validation: Certificate issuer isn't Trusted.\n\
+            Signature #2:\n  \
+              - Signer Certificate Common Name: John Roe\n  \
+              - Signed Ranges: [0 - 20000], [24000 - 30000]\n  \
+              - Total document signed\n  \
+              - Signature Validation: Digest Mismatch.\n  \
+              - Certificate Validation: Certificate is Trusted.\n";
+
+        let signatures = parse_pdfsig_output(output);
+        assert_eq!(signatures.len(), 2);
+        assert_eq!(signatures[0].signer_name.as_deref(), Some("Jane Doe"));
+        assert_eq!(signatures[0].signer_dn.as_deref(), Some("CN=Jane Doe,O=ACME Corp,C=US"));
+        assert_eq!(signatures[0].signing_time.as_deref(), Some("Jan 15 2024 10:00:00"));
+        assert!(signatures[0].signature_valid);
+        assert!(!signatures[0].certificate_trusted);
+        assert!(!signatures[0].covers_whole_document);
+        assert!(!signatures[1].signature_valid);
+        assert_eq!(signatures[1].signature_status, "Digest Mismatch.");
+        assert!(signatures[1].certificate_trusted);
+        assert!(signatures[1].covers_whole_document);
+
+        assert!(parse_pdfsig_output("File 'scan.pdf' does not contain any signatures\n").is_empty());
+    }
+
+    #[test]
+    fn test_might_be_signed() {
+        assert!(might_be_signed(b"%PDF-1.7 << /Type /Sig /ByteRange [0 100 200 50] >>"));
+        assert!(!might_be_signed(b"%PDF-1.7 << /Type /Catalog >>"));
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::db::Database;
+use crate::models::{Document, DocumentPiiResponse, NewPiiFinding, PiiFinding, PiiKind, RedactDocumentRequest};
+use crate::ocr::{pii, PAGE_BREAK};
+use crate::services::llm::llm_service::{LLMService, PromptTemplate};
+
+/// Whether to scan documents for personal data after OCR
+static ENABLED_AFTER_OCR: Lazy<bool> = Lazy::new(|| env_flag("PII_SCAN_ENABLED"));
+
+/// Whether scans ask the LLM as well as matching patterns, unless a request says otherwise
+static USE_LLM: Lazy<bool> = Lazy::new(|| env_flag("PII_SCAN_USE_LLM"));
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+}
+
+/// Characters of a document's text shown to the LLM
+const LLM_TEXT_CHARS: usize = 8000;
+
+const LLM_SYSTEM_PROMPT: &str = "You find personal data in documents. Answer with JSON only, in this form: \
+    [{\"kind\": \"ssn|iban|credit_card|email|phone\", \"text\": \"<exactly as written in the document>\"}]. \
+    Only list social security numbers, IBANs, credit card numbers, email addresses and phone numbers. \
+    Answer [] when there are none.";
+const LLM_PROMPT: PromptTemplate = PromptTemplate::new("pii", 1);
+
+/// Finds social security numbers, IBANs, card numbers, email addresses and phone
+/// numbers in a document's text, with patterns and optionally the LLM
+pub struct PiiService {
+    db: Database,
+    llm_service: LLMService,
+}
+
+impl PiiService {
+    pub fn new(db: Database) -> Self {
+        let llm_service = LLMService::new(db.get_pool().clone());
+        Self { db, llm_service }
+    }
+
+    /// Whether documents should be scanned automatically after OCR
+    pub fn enabled_after_ocr() -> bool {
+        *ENABLED_AFTER_OCR
+    }
+
+    /// Whether scans use the LLM when the request does not say
+    pub fn use_llm_by_default() -> bool {
+        *USE_LLM
+    }
+
+    /// Whether the document has text that can be scanned
+    pub fn has_text(document: &Document) -> bool {
+        document_text(document).is_some()
+    }
+
+    /// Scan the document's text and replace its earlier findings. The LLM is only
+    /// asked when `use_llm` is set and a chat model is configured; if it fails, the
+    /// pattern findings are kept.
+    pub async fn scan(&self, document: &Document, use_llm: bool) -> Result<DocumentPiiResponse> {
+        let text = document_text(document)
+            .ok_or_else(|| anyhow!("Document {} has no text to scan for personal data", document.id))?;
+
+        let mut findings: Vec<NewPiiFinding> = pii::detect(text)
+            .into_iter()
+            .map(|found| new_finding(text, found.kind, found.start, found.end, "regex"))
+            .collect();
+
+        let mut llm_used = false;
+        if use_llm && self.llm_service.chat_enabled() {
+            match self.find_with_llm(text).await {
+                Ok(found) => {
+                    llm_used = true;
+                    merge_llm_findings(text, &mut findings, &found);
+                }
+                Err(e) => warn!("LLM scan for personal data failed for document {}: {}", document.id, e),
+            }
+        }
+
+        let stored = self.db.replace_pii_findings(document.id, &findings, llm_used).await?;
+        info!("Found {} pieces of personal data in document {}", stored.len(), document.id);
+        self.findings_response(document.id).await
+    }
+
+    /// Scan a freshly processed document with the configured defaults
+    pub async fn scan_after_ocr(&self, document: &Document) -> Result<()> {
+        if !Self::has_text(document) {
+            return Ok(());
+        }
+        self.scan(document, Self::use_llm_by_default()).await?;
+        Ok(())
+    }
+
+    /// What the last scan of the document found
+    pub async fn findings_response(&self, document_id: uuid::Uuid) -> Result<DocumentPiiResponse> {
+        let scan = self.db.get_pii_scan(document_id).await?;
+        let findings = self.db.get_pii_findings(document_id).await?;
+        Ok(DocumentPiiResponse {
+            document_id,
+            scanned_at: scan.map(|(scanned_at, _)| scanned_at),
+            llm_used: scan.is_some_and(|(_, llm_used)| llm_used),
+            suggested_redaction: suggested_redaction(&findings),
+            findings,
+        })
+    }
+
+    async fn find_with_llm(&self, text: &str) -> Result<Vec<(PiiKind, String)>> {
+        let excerpt: String = text.chars().take(LLM_TEXT_CHARS).collect();
+        let answer = self
+            .llm_service
+            .complete(LLM_PROMPT, LLM_SYSTEM_PROMPT, &excerpt)
+            .await
+            .map_err(|e| anyhow!(e))?;
+
+        parse_llm_answer(&answer).ok_or_else(|| anyhow!("LLM did not answer with a list of findings"))
+    }
+}
+
+/// OCR text, or the content of documents that have no OCR text
+fn document_text(document: &Document) -> Option<&str> {
+    let usable = |text: &&str| !text.trim().is_empty();
+    document
+        .ocr_text
+        .as_deref()
+        .filter(usable)
+        .or_else(|| document.content.as_deref().filter(usable))
+}
+
+fn new_finding(text: &str, kind: PiiKind, start: usize, end: usize, detected_by: &'static str) -> NewPiiFinding {
+    let paginated = text.contains(PAGE_BREAK);
+    NewPiiFinding {
+        kind,
+        text: text[start..end].to_string(),
+        start_offset: start as i32,
+        end_offset: end as i32,
+        page_number: paginated.then(|| text[..start].matches(PAGE_BREAK).count() as i32 + 1),
+        detected_by,
+    }
+}
+
+/// `(kind, text)` pairs of the LLM's JSON answer, skipping kinds it was not asked for
+fn parse_llm_answer(answer: &str) -> Option<Vec<(PiiKind, String)>> {
+    let answer: Value = serde_json::from_str(answer).ok()?;
+    let found = answer
+        .as_array()?
+        .iter()
+        .filter_map(|item| {
+            let kind = PiiKind::from_name(item.get("kind")?.as_str()?)?;
+            let text = item.get("text")?.as_str()?.trim();
+            (!text.is_empty()).then(|| (kind, text.to_string()))
+        })
+        .collect();
+    Some(found)
+}
+
+/// Add every occurrence of what the LLM found that the patterns did not already cover.
+/// Texts the LLM made up or changed are not in the document and are dropped.
+fn merge_llm_findings(text: &str, findings: &mut Vec<NewPiiFinding>, found: &[(PiiKind, String)]) {
+    for (kind, needle) in found {
+        for (start, matched) in text.match_indices(needle.as_str()) {
+            let (start, end) = (start as i32, (start + matched.len()) as i32);
+            if findings.iter().any(|f| start < f.end_offset && f.start_offset < end) {
+                continue;
+            }
+            findings.push(new_finding(text, *kind, start as usize, end as usize, "llm"));
+        }
+    }
+    findings.sort_by_key(|finding| finding.start_offset);
+}
+
+/// A redaction of every distinct text that was found
+fn suggested_redaction(findings: &[PiiFinding]) -> RedactDocumentRequest {
+    let mut terms: Vec<String> = Vec::new();
+    for finding in findings {
+        if !terms.contains(&finding.text) {
+            terms.push(finding.text.clone());
+        }
+    }
+    RedactDocumentRequest { regions: Vec::new(), terms }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_llm_findings() {
+        let text = "Page one: call 555 0100 or mail jane@example.com\u{c}Page two: jane@example.com, 555 0100";
+        let mut findings: Vec<NewPiiFinding> = pii::detect(text)
+            .into_iter()
+            .map(|found| new_finding(text, found.kind, found.start, found.end, "regex"))
+            .collect();
+        assert_eq!(findings.len(), 2);
+
+        let found = parse_llm_answer(
+            r#"[{"kind": "phone", "text": "555 0100"}, {"kind": "email", "text": "jane@example.com"},
+                {"kind": "passport", "text": "X123"}, {"kind": "phone", "text": "not in the text"}]"#,
+        )
+        .unwrap();
+        assert_eq!(found.len(), 3);
+
+        merge_llm_findings(text, &mut findings, &found);
+        let summary: Vec<(PiiKind, Option<i32>, &str)> =
+            findings.iter().map(|f| (f.kind, f.page_number, f.detected_by)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (PiiKind::Phone, Some(1), "llm"),
+                (PiiKind::Email, Some(1), "regex"),
+                (PiiKind::Email, Some(2), "regex"),
+                (PiiKind::Phone, Some(2), "llm"),
+            ]
+        );
+    }
+}
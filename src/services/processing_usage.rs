@@ -0,0 +1,63 @@
+//! Attributing processing to documents. Work done for a document, its OCR job, the
+//! analysis steps after it or an LLM request about it, runs inside [`for_document`], so
+//! the LLM requests it makes are recorded against the document wherever they are sent
+//! from.
+
+use std::future::Future;
+
+use uuid::Uuid;
+
+tokio::task_local! {
+    static DOCUMENT: Uuid;
+}
+
+/// Run work done for the document, recording the LLM requests it makes against it
+pub async fn for_document<F: Future>(document_id: Uuid, work: F) -> F::Output {
+    DOCUMENT.scope(document_id, work).await
+}
+
+/// The document the running work is done for, if any
+pub fn current_document() -> Option<Uuid> {
+    DOCUMENT.try_with(|document_id| *document_id).ok()
+}
+
+/// Prompt and completion tokens an OpenAI-compatible API reported for a completion
+pub fn llm_tokens(response: &serde_json::Value) -> (i64, i64) {
+    let usage = &response["usage"];
+    (
+        usage["prompt_tokens"].as_i64().unwrap_or(0),
+        usage["completion_tokens"].as_i64().unwrap_or(0),
+    )
+}
+
+/// Price of the tokens at the given prices per 1,000 input and output tokens
+pub fn llm_cost(prompt_tokens: i64, completion_tokens: i64, prices: Option<(f64, f64)>) -> Option<f64> {
+    prices.map(|(input, output)| (prompt_tokens as f64 * input + completion_tokens as f64 * output) / 1000.0)
+}
+
+/// Price of 1,000 pages of cloud OCR from `OCR_COST_PER_1K_PAGES`, for estimates
+pub fn ocr_cost_per_1k_pages() -> Option<f64> {
+    std::env::var("OCR_COST_PER_1K_PAGES").ok().and_then(|v| v.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_work_is_attributed_to_its_document() {
+        let document_id = Uuid::new_v4();
+        assert_eq!(current_document(), None);
+        assert_eq!(for_document(document_id, async { current_document() }).await, Some(document_id));
+    }
+
+    #[test]
+    fn test_llm_tokens_and_cost() {
+        let response = serde_json::json!({"usage": {"prompt_tokens": 1200, "completion_tokens": 300}});
+        let (prompt, completion) = llm_tokens(&response);
+        assert_eq!((prompt, completion), (1200, 300));
+        assert_eq!(llm_cost(prompt, completion, Some((0.5, 2.0))), Some(1.2));
+        assert_eq!(llm_cost(prompt, completion, None), None);
+        assert_eq!(llm_tokens(&serde_json::json!({})), (0, 0));
+    }
+}
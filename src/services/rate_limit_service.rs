@@ -0,0 +1,414 @@
+//! Token bucket rate limiting for internet-exposed instances.
+//!
+//! - Auth endpoints (logins, registration, passkeys and public share links) allow
+//!   `RATE_LIMIT_AUTH_PER_MINUTE` requests per client IP, default 10.
+//! - Failed password logins are limited per account: `RATE_LIMIT_ACCOUNT_FAILURES`
+//!   failures, default 10, refilled over `RATE_LIMIT_ACCOUNT_WINDOW_MINUTES`, default 15.
+//! - All other API requests are throttled per client IP once
+//!   `RATE_LIMIT_REQUESTS_PER_MINUTE` is set, with bursts of `RATE_LIMIT_BURST_SIZE`.
+//!   Paths starting with an entry of `RATE_LIMIT_EXCLUDE_PATHS` are never throttled.
+//!
+//! `RATE_LIMIT_ENABLED=false` turns everything off. Buckets live in memory unless
+//! `RATE_LIMIT_BACKEND=postgres`, which shares them between instances.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::db::Database;
+use crate::services::audit_service::ClientInfo;
+use crate::AppState;
+
+/// Path prefixes counted against the per-IP auth limit
+const AUTH_PATHS: [&str; 5] = [
+    "/api/auth/login",
+    "/api/auth/register",
+    "/api/auth/passkey",
+    "/api/auth/oidc/callback",
+    "/api/public/shares",
+];
+/// In-memory buckets kept before full ones, and then the least recently used, are dropped
+const MAX_MEMORY_BUCKETS: usize = 10_000;
+/// Postgres buckets are pruned after this many requests
+const PRUNE_EVERY: u64 = 1_000;
+
+/// A bucket holding up to `capacity` tokens, refilled at `per_second`. Each request takes one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub capacity: f64,
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    /// `requests` per minute on average with bursts of up to `burst`
+    pub fn per_minute(requests: u32, burst: u32) -> Self {
+        RateLimit {
+            capacity: f64::from(burst.max(1)),
+            per_second: f64::from(requests.max(1)) / 60.0,
+        }
+    }
+
+    /// `count` requests, refilled evenly over `window`
+    pub fn per_window(count: u32, window: Duration) -> Self {
+        let count = count.max(1);
+        RateLimit {
+            capacity: f64::from(count),
+            per_second: f64::from(count) / window.as_secs_f64().max(1.0),
+        }
+    }
+
+    /// Time until a bucket holding `tokens` has a whole token again
+    pub fn retry_after(&self, tokens: f64) -> Duration {
+        let seconds = ((1.0 - tokens) / self.per_second).ceil().max(1.0);
+        Duration::from_secs(seconds as u64)
+    }
+
+    /// Time for an empty bucket to fill up
+    fn refill_time(&self) -> f64 {
+        self.capacity / self.per_second
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateLimitBackend {
+    Memory,
+    Postgres,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub backend: RateLimitBackend,
+    /// Per-IP limit of API requests; None leaves the API unthrottled
+    pub api: Option<RateLimit>,
+    pub exclude_paths: Vec<String>,
+    /// Per-IP limit of auth requests
+    pub auth: RateLimit,
+    /// Per-account limit of failed logins
+    pub account_failures: RateLimit,
+}
+
+impl RateLimitConfig {
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let number = |name: &str| {
+            lookup(name).and_then(|value| match value.trim().parse::<u32>() {
+                Ok(n) if n > 0 => Some(n),
+                _ => {
+                    warn!("Ignoring invalid {}: '{}'", name, value);
+                    None
+                }
+            })
+        };
+
+        let api = number("RATE_LIMIT_REQUESTS_PER_MINUTE").map(|requests| {
+            RateLimit::per_minute(requests, number("RATE_LIMIT_BURST_SIZE").unwrap_or(requests))
+        });
+        let auth_per_minute = number("RATE_LIMIT_AUTH_PER_MINUTE").unwrap_or(10);
+        let account_window = number("RATE_LIMIT_ACCOUNT_WINDOW_MINUTES").unwrap_or(15);
+
+        RateLimitConfig {
+            enabled: lookup("RATE_LIMIT_ENABLED").is_none_or(|value| value.trim().to_lowercase() != "false"),
+            backend: match lookup("RATE_LIMIT_BACKEND").as_deref().map(str::trim) {
+                Some("postgres") => RateLimitBackend::Postgres,
+                _ => RateLimitBackend::Memory,
+            },
+            api,
+            exclude_paths: lookup("RATE_LIMIT_EXCLUDE_PATHS")
+                .unwrap_or_else(|| "/api/health,/metrics".to_string())
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect(),
+            auth: RateLimit::per_minute(auth_per_minute, auth_per_minute),
+            account_failures: RateLimit::per_window(
+                number("RATE_LIMIT_ACCOUNT_FAILURES").unwrap_or(10),
+                Duration::from_secs(u64::from(account_window) * 60),
+            ),
+        }
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude_paths.iter().any(|prefix| path.starts_with(prefix.as_str()))
+    }
+}
+
+static CONFIG: Lazy<RateLimitConfig> = Lazy::new(|| RateLimitConfig::from_lookup(|name| std::env::var(name).ok()));
+static MEMORY_BUCKETS: Lazy<Mutex<HashMap<String, MemoryBucket>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static POSTGRES_REQUESTS: AtomicU64 = AtomicU64::new(0);
+
+pub fn config() -> &'static RateLimitConfig {
+    &CONFIG
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    pub(crate) fn full(limit: &RateLimit, now: Instant) -> Self {
+        Bucket { tokens: limit.capacity, updated: now }
+    }
+
+    fn refilled(&self, limit: &RateLimit, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        (self.tokens + elapsed * limit.per_second).min(limit.capacity)
+    }
+}
+
+/// An in-memory bucket with the limit it is counted against
+#[derive(Debug, Clone, Copy)]
+struct MemoryBucket {
+    bucket: Bucket,
+    limit: RateLimit,
+}
+
+/// Makes room for a new bucket: drops the buckets that have filled up again, and if
+/// active clients still fill the map, the half that was used longest ago
+fn prune_memory_buckets(buckets: &mut HashMap<String, MemoryBucket>, now: Instant) {
+    buckets.retain(|_, entry| entry.bucket.refilled(&entry.limit, now) < entry.limit.capacity);
+    if buckets.len() >= MAX_MEMORY_BUCKETS {
+        let mut updated: Vec<Instant> = buckets.values().map(|entry| entry.bucket.updated).collect();
+        let middle = updated.len() / 2;
+        let (_, cutoff, _) = updated.select_nth_unstable(middle);
+        let cutoff = *cutoff;
+        buckets.retain(|_, entry| entry.bucket.updated > cutoff);
+    }
+}
+
+/// Takes a token from `bucket`, or tells how long until one is available
+pub(crate) fn take_token(bucket: &mut Bucket, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+    let tokens = bucket.refilled(limit, now);
+    bucket.updated = now;
+    if tokens >= 1.0 {
+        bucket.tokens = tokens - 1.0;
+        Ok(())
+    } else {
+        bucket.tokens = tokens;
+        Err(limit.retry_after(tokens))
+    }
+}
+
+/// Takes a token from the bucket under `key`; Err holds the time to wait. Errors of the
+/// Postgres backend let the request through.
+pub async fn take(db: &Database, key: &str, limit: &RateLimit) -> Result<(), Duration> {
+    match config().backend {
+        RateLimitBackend::Memory => {
+            let now = Instant::now();
+            let mut buckets = MEMORY_BUCKETS.lock().unwrap_or_else(|e| e.into_inner());
+            if buckets.len() >= MAX_MEMORY_BUCKETS && !buckets.contains_key(key) {
+                prune_memory_buckets(&mut buckets, now);
+            }
+            let entry = buckets.entry(key.to_string()).or_insert(MemoryBucket {
+                bucket: Bucket::full(limit, now),
+                limit: *limit,
+            });
+            entry.limit = *limit;
+            take_token(&mut entry.bucket, limit, now)
+        }
+        RateLimitBackend::Postgres => {
+            if POSTGRES_REQUESTS.fetch_add(1, Ordering::Relaxed) % PRUNE_EVERY == 0 {
+                let idle = [config().auth, config().account_failures]
+                    .iter()
+                    .chain(config().api.iter())
+                    .map(RateLimit::refill_time)
+                    .fold(0.0, f64::max);
+                if let Err(e) = db.prune_rate_limit_buckets(idle).await {
+                    warn!("Failed to prune rate limit buckets: {}", e);
+                }
+            }
+            match db.take_rate_limit_token(key, limit.capacity, limit.per_second).await {
+                Ok((true, _)) => Ok(()),
+                Ok((false, tokens)) => Err(limit.retry_after(tokens)),
+                Err(e) => {
+                    warn!("Rate limit check for {} failed, allowing the request: {}", key, e);
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Whether the bucket under `key` has a token left, without taking it
+pub async fn peek(db: &Database, key: &str, limit: &RateLimit) -> Result<(), Duration> {
+    let tokens = match config().backend {
+        RateLimitBackend::Memory => {
+            let buckets = MEMORY_BUCKETS.lock().unwrap_or_else(|e| e.into_inner());
+            buckets
+                .get(key)
+                .map_or(limit.capacity, |entry| entry.bucket.refilled(limit, Instant::now()))
+        }
+        RateLimitBackend::Postgres => match db.peek_rate_limit_tokens(key, limit.capacity, limit.per_second).await {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                warn!("Rate limit check for {} failed, allowing the request: {}", key, e);
+                limit.capacity
+            }
+        },
+    };
+
+    if tokens >= 1.0 {
+        Ok(())
+    } else {
+        Err(limit.retry_after(tokens))
+    }
+}
+
+fn account_key(username: &str) -> String {
+    format!("account:{}", username.trim().to_lowercase())
+}
+
+/// Whether the account may attempt another login
+pub async fn check_account(db: &Database, username: &str) -> Result<(), Duration> {
+    if !config().enabled {
+        return Ok(());
+    }
+    peek(db, &account_key(username), &config().account_failures).await
+}
+
+/// Counts a failed login against the account
+pub async fn record_account_failure(db: &Database, username: &str) {
+    if config().enabled {
+        let _ = take(db, &account_key(username), &config().account_failures).await;
+    }
+}
+
+/// 429 with a `Retry-After` header in seconds
+pub fn too_many_requests(retry_after: Duration) -> Response {
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, "Too many requests, try again later").into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after.as_secs()));
+    response
+}
+
+/// Applies the per-IP auth and API limits to every request
+pub async fn rate_limit_middleware(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = config();
+    let path = request.uri().path();
+    let Some(ip) = client.ip_address.as_deref() else {
+        return next.run(request).await;
+    };
+    if !config.enabled || !path.starts_with("/api/") || config.is_excluded(path) {
+        return next.run(request).await;
+    }
+
+    let limited = if AUTH_PATHS.iter().any(|prefix| path.starts_with(prefix)) {
+        take(&state.db, &format!("auth-ip:{}", ip), &config.auth).await
+    } else if let Some(api) = &config.api {
+        take(&state.db, &format!("ip:{}", ip), api).await
+    } else {
+        Ok(())
+    };
+
+    match limited {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            warn!("Rate limited {} {} from {}", request.method(), path, ip);
+            too_many_requests(retry_after)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_bursts_then_refills() {
+        let limit = RateLimit::per_minute(60, 3);
+        let start = Instant::now();
+        let mut bucket = Bucket { tokens: limit.capacity, updated: start };
+
+        for _ in 0..3 {
+            assert!(take_token(&mut bucket, &limit, start).is_ok());
+        }
+        assert_eq!(take_token(&mut bucket, &limit, start), Err(Duration::from_secs(1)));
+        assert!(take_token(&mut bucket, &limit, start + Duration::from_secs(1)).is_ok());
+        assert!(take_token(&mut bucket, &limit, start + Duration::from_secs(1)).is_err());
+
+        // Never refills beyond the burst size
+        assert_eq!(bucket.refilled(&limit, start + Duration::from_secs(3600)), 3.0);
+    }
+
+    #[test]
+    fn test_memory_buckets_pruned_by_their_own_limit() {
+        let start = Instant::now();
+        let slow = RateLimit::per_window(10, Duration::from_secs(15 * 60));
+        let fast = RateLimit::per_minute(600, 10);
+        let mut buckets = HashMap::new();
+        for (key, limit) in [("account:slow", slow), ("ip:fast", fast)] {
+            let mut bucket = Bucket::full(&limit, start);
+            take_token(&mut bucket, &limit, start).unwrap();
+            buckets.insert(key.to_string(), MemoryBucket { bucket, limit });
+        }
+
+        // The fast bucket has filled up again after a second, the slow one has not
+        prune_memory_buckets(&mut buckets, start + Duration::from_secs(1));
+        assert!(buckets.contains_key("account:slow"));
+        assert!(!buckets.contains_key("ip:fast"));
+
+        // Active buckets beyond the cap are dropped oldest first
+        for i in 0..MAX_MEMORY_BUCKETS {
+            let updated = start + Duration::from_millis(i as u64);
+            let bucket = Bucket { tokens: 0.0, updated };
+            buckets.insert(format!("ip:{}", i), MemoryBucket { bucket, limit: slow });
+        }
+        prune_memory_buckets(&mut buckets, start + Duration::from_secs(1));
+        assert!(buckets.len() < MAX_MEMORY_BUCKETS);
+        assert!(buckets.contains_key(&format!("ip:{}", MAX_MEMORY_BUCKETS - 1)));
+        assert!(!buckets.contains_key("ip:0"));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let limit = RateLimit::per_window(10, Duration::from_secs(15 * 60));
+        // One token every 90 seconds
+        assert_eq!(limit.retry_after(0.0), Duration::from_secs(90));
+        assert_eq!(limit.retry_after(0.5), Duration::from_secs(45));
+        assert_eq!(limit.retry_after(0.999_999), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_config_defaults_and_overrides() {
+        let defaults = RateLimitConfig::from_lookup(|_| None);
+        assert!(defaults.enabled);
+        assert_eq!(defaults.backend, RateLimitBackend::Memory);
+        assert_eq!(defaults.api, None);
+        assert_eq!(defaults.auth, RateLimit::per_minute(10, 10));
+        assert!(defaults.is_excluded("/api/health"));
+
+        let env: HashMap<&str, &str> = [
+            ("RATE_LIMIT_ENABLED", "FALSE"),
+            ("RATE_LIMIT_BACKEND", "postgres"),
+            ("RATE_LIMIT_REQUESTS_PER_MINUTE", "120"),
+            ("RATE_LIMIT_BURST_SIZE", "0"),
+            ("RATE_LIMIT_EXCLUDE_PATHS", "/api/documents/thumbnails, /api/events"),
+        ]
+        .into_iter()
+        .collect();
+        let config = RateLimitConfig::from_lookup(|name| env.get(name).map(|v| v.to_string()));
+        assert!(!config.enabled);
+        assert_eq!(config.backend, RateLimitBackend::Postgres);
+        // An invalid burst size falls back to the per-minute rate
+        assert_eq!(config.api, Some(RateLimit::per_minute(120, 120)));
+        assert!(config.is_excluded("/api/events/stream"));
+        assert!(!config.is_excluded("/api/health"));
+    }
+}
@@ -0,0 +1,297 @@
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::models::{Document, RedactDocumentRequest, User, UserRole};
+use crate::services::file_service::FileService;
+
+/// Resolution pages are rendered at; redacted PDFs are rebuilt from these images
+#[cfg(feature = "ocr")]
+const REDACTION_DPI: u32 = 300;
+/// Pixels blacked out around a matched word so no stroke of it is left
+const WORD_PADDING: u32 = 3;
+
+/// Formats that can be redacted: PDFs are rebuilt from page images, images are
+/// written back in their own format
+pub fn supports(mime_type: &str) -> bool {
+    matches!(mime_type, "application/pdf" | "image/png" | "image/jpeg" | "image/tiff" | "image/bmp")
+}
+
+/// Whether the user may see contents from before a redaction: the document's owner
+/// and admins
+pub fn may_see_unredacted(user: &User, document: &Document) -> bool {
+    user.role == UserRole::Admin || document.user_id == user.id
+}
+
+/// A word Tesseract recognized on a page, with its pixel box
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrWord {
+    pub text: String,
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    /// 0 to 100
+    pub confidence: f32,
+}
+
+/// The words of Tesseract's TSV output, in reading order
+pub fn parse_tesseract_tsv(tsv: &str) -> Vec<OcrWord> {
+    tsv.lines()
+        .skip(1)
+        .filter_map(|line| {
+            // level page block paragraph line word left top width height conf text
+            let columns: Vec<&str> = line.splitn(12, '\t').collect();
+            if columns.len() < 12 || columns[0] != "5" {
+                return None;
+            }
+            let number = |i: usize| columns[i].trim().parse::<u32>().ok();
+            let text = columns[11].trim();
+            if text.is_empty() {
+                return None;
+            }
+            Some(OcrWord {
+                text: text.to_string(),
+                left: number(6)?,
+                top: number(7)?,
+                width: number(8)?,
+                height: number(9)?,
+                confidence: columns[10].trim().parse::<f32>().ok()?.max(0.0),
+            })
+        })
+        .collect()
+}
+
+/// Lowercase letters and digits only, so "Doe," matches "doe" and "555-1234" matches "5551234"
+fn normalize(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Pixel boxes (x, y, width, height) of every word that is part of an occurrence of
+/// one of the terms, and how many occurrences there were. Terms of several words
+/// match consecutive words.
+pub fn find_term_boxes(words: &[OcrWord], terms: &[String]) -> (Vec<(u32, u32, u32, u32)>, usize) {
+    let normalized: Vec<String> = words.iter().map(|word| normalize(&word.text)).collect();
+    let terms: Vec<Vec<String>> = terms
+        .iter()
+        .map(|term| term.split_whitespace().map(normalize).filter(|t| !t.is_empty()).collect::<Vec<_>>())
+        .filter(|tokens| !tokens.is_empty())
+        .collect();
+
+    let mut redacted = vec![false; words.len()];
+    let mut matches = 0;
+    for start in 0..words.len() {
+        for tokens in &terms {
+            let end = start + tokens.len();
+            if end <= words.len() && normalized[start..end] == tokens[..] {
+                matches += 1;
+                redacted[start..end].iter_mut().for_each(|r| *r = true);
+            }
+        }
+    }
+
+    let boxes = words
+        .iter()
+        .zip(redacted)
+        .filter(|(_, redacted)| *redacted)
+        .map(|(word, _)| {
+            let left = word.left.saturating_sub(WORD_PADDING);
+            let top = word.top.saturating_sub(WORD_PADDING);
+            (left, top, word.width + (word.left - left) + WORD_PADDING, word.height + (word.top - top) + WORD_PADDING)
+        })
+        .collect();
+    (boxes, matches)
+}
+
+/// What a redaction removed
+pub struct RedactionOutcome {
+    pub document: Document,
+    /// Version the unredacted contents were kept as
+    pub original_version: i32,
+    pub term_matches: usize,
+    pub pages_redacted: Vec<u32>,
+}
+
+/// Burns redactions into a document: pages are rendered to images, the regions and
+/// terms are blacked out, and the result replaces the document's contents. The
+/// unredacted contents are kept as a restricted version.
+pub struct RedactionService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl RedactionService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    pub async fn page_count(&self, document: &Document) -> Result<u32> {
+        if document.mime_type != "application/pdf" {
+            return Ok(1);
+        }
+        let data = self.file_service.read_file(&document.file_path).await?;
+        crate::ocr::pdf_pages::TempPdf::from_bytes(&data).await?.page_count().await
+    }
+
+    /// Redact the document. Terms are looked up with OCR in `language`. None when
+    /// nothing was found to redact, in which case the document is unchanged.
+    #[cfg(feature = "ocr")]
+    pub async fn redact(
+        &self,
+        document: &Document,
+        request: &RedactDocumentRequest,
+        language: &str,
+    ) -> Result<Option<RedactionOutcome>> {
+        use crate::ocr::page_images;
+        use crate::services::document_version_service::DocumentVersionService;
+        use crate::services::region_ocr_service::PageRegion;
+
+        if !supports(&document.mime_type) {
+            anyhow::bail!("{} documents cannot be redacted", document.mime_type);
+        }
+
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let page_count = self.page_count(document).await?;
+        let terms: Vec<String> = request.terms.iter().filter(|term| !term.trim().is_empty()).cloned().collect();
+
+        let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+        let work_dir = std::path::PathBuf::from(temp_dir).join(format!("redact_{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&work_dir).await?;
+
+        let result: Result<Option<(Vec<u8>, usize, Vec<u32>)>> = async {
+            let mut page_files = Vec::with_capacity(page_count as usize);
+            let mut pages_redacted = Vec::new();
+            let mut term_matches = 0;
+            let mut last_page = None;
+
+            // One page at a time, so long documents do not have to fit in memory
+            for page in 1..=page_count {
+                let rendered = page_images::render_page_range(&data, &document.mime_type, REDACTION_DPI, page, page).await?;
+                let image = rendered
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Page {} could not be rendered", page))?;
+                let mut pixels = image.to_rgb8();
+                let (width, height) = pixels.dimensions();
+
+                let mut boxes: Vec<(u32, u32, u32, u32)> = request
+                    .regions
+                    .iter()
+                    .filter(|region| region.page == page)
+                    .filter_map(|region| {
+                        PageRegion { x: region.x, y: region.y, width: region.width, height: region.height }
+                            .to_pixels(width, height)
+                    })
+                    .collect();
+                if !terms.is_empty() {
+                    let words = parse_tesseract_tsv(&page_images::ocr_image_tsv(&image, language).await?);
+                    let (word_boxes, matches) = find_term_boxes(&words, &terms);
+                    boxes.extend(word_boxes);
+                    term_matches += matches;
+                }
+
+                if !boxes.is_empty() {
+                    for (x, y, w, h) in boxes {
+                        let w = w.min(width.saturating_sub(x)).max(1);
+                        let h = h.min(height.saturating_sub(y)).max(1);
+                        imageproc::drawing::draw_filled_rect_mut(
+                            &mut pixels,
+                            imageproc::rect::Rect::at(x as i32, y as i32).of_size(w, h),
+                            image::Rgb([0, 0, 0]),
+                        );
+                    }
+                    pages_redacted.push(page);
+                }
+
+                let page_file = work_dir.join(format!("page-{:05}.png", page));
+                pixels.save_with_format(&page_file, image::ImageFormat::Png)?;
+                page_files.push(page_file);
+                last_page = Some(pixels);
+            }
+
+            if pages_redacted.is_empty() {
+                return Ok(None);
+            }
+
+            let redacted = if document.mime_type == "application/pdf" {
+                page_images::images_to_pdf(&page_files, language).await?
+            } else {
+                let format = image::ImageFormat::from_mime_type(&document.mime_type).unwrap_or(image::ImageFormat::Png);
+                let image = image::DynamicImage::ImageRgb8(last_page.expect("images have one page"));
+                let mut buffer = Vec::new();
+                image.write_to(&mut std::io::Cursor::new(&mut buffer), format)?;
+                buffer
+            };
+            Ok(Some((redacted, term_matches, pages_redacted)))
+        }
+        .await;
+        let _ = tokio::fs::remove_dir_all(&work_dir).await;
+
+        let Some((redacted, term_matches, pages_redacted)) = result? else {
+            return Ok(None);
+        };
+
+        let versions = DocumentVersionService::new(self.db.clone(), self.file_service.clone());
+        let updated = versions.add_version(document, &redacted, document.original_modified_at).await?;
+        let original_version = self
+            .db
+            .get_document_versions(document.id)
+            .await?
+            .first()
+            .map(|version| version.version_number)
+            .ok_or_else(|| anyhow::anyhow!("Unredacted contents of document {} were not kept", document.id))?;
+        // Earlier versions hold the redacted contents as well
+        self.db.restrict_document_versions(document.id).await?;
+
+        tracing::info!(
+            "Redacted {} pages of document {}; the original is version {}",
+            pages_redacted.len(),
+            document.id,
+            original_version
+        );
+        Ok(Some(RedactionOutcome { document: updated, original_version, term_matches, pages_redacted }))
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub async fn redact(
+        &self,
+        _document: &Document,
+        _request: &RedactDocumentRequest,
+        _language: &str,
+    ) -> Result<Option<RedactionOutcome>> {
+        anyhow::bail!("Redaction requires OCR feature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, left: u32) -> OcrWord {
+        OcrWord { text: text.to_string(), left, top: 100, width: 40, height: 20, confidence: 90.0 }
+    }
+
+    #[test]
+    fn test_parse_tesseract_tsv() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   4\t1\t1\t1\t1\t0\t10\t20\t300\t30\t-1\t\n\
+                   5\t1\t1\t1\t1\t1\t10\t20\t80\t30\t96.1\tJane\n\
+                   5\t1\t1\t1\t1\t2\t95\t20\t60\t30\t95.3\tDoe,\n\
+                   5\t1\t1\t1\t1\t3\t160\t20\t10\t30\t12.0\t \n";
+        let words = parse_tesseract_tsv(tsv);
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[1], OcrWord { text: "Doe,".to_string(), left: 95, top: 20, width: 60, height: 30, confidence: 95.3 });
+    }
+
+    #[test]
+    fn test_find_term_boxes() {
+        let words = vec![word("Call", 0), word("Jane", 50), word("Doe,", 100), word("or", 150), word("JANE", 200)];
+        let terms = vec!["jane doe".to_string(), "  ".to_string()];
+        let (boxes, matches) = find_term_boxes(&words, &terms);
+        assert_eq!(matches, 1);
+        assert_eq!(boxes, vec![(47, 97, 46, 26), (97, 97, 46, 26)]);
+
+        let (boxes, matches) = find_term_boxes(&words, &["Jane".to_string()]);
+        assert_eq!((boxes.len(), matches), (2, 2));
+        assert_eq!(find_term_boxes(&words, &["Smith".to_string()]).1, 0);
+    }
+}
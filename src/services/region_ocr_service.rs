@@ -0,0 +1,148 @@
+use anyhow::Result;
+
+use crate::models::Document;
+use crate::services::file_service::FileService;
+
+/// Resolution used when the caller does not ask for one
+pub const DEFAULT_REGION_DPI: u32 = 400;
+const MIN_REGION_DPI: u32 = 150;
+const MAX_REGION_DPI: u32 = 600;
+/// Crops smaller than this on their short side are upscaled before recognition
+const MIN_CROP_SIDE: u32 = 300;
+
+/// A rectangle on a page, as fractions (0-1) of the page's width and height
+/// measured from the top-left corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PageRegion {
+    pub fn is_valid(&self) -> bool {
+        let values = [self.x, self.y, self.width, self.height];
+        values.iter().all(|v| v.is_finite())
+            && self.x >= 0.0
+            && self.y >= 0.0
+            && self.width > 0.0
+            && self.height > 0.0
+            && self.x + self.width <= 1.0 + 1e-6
+            && self.y + self.height <= 1.0 + 1e-6
+    }
+
+    /// Pixel rectangle (x, y, width, height) on a page of the given size,
+    /// or None when it covers less than one pixel
+    pub fn to_pixels(&self, page_width: u32, page_height: u32) -> Option<(u32, u32, u32, u32)> {
+        let left = (self.x * page_width as f64).floor().max(0.0) as u32;
+        let top = (self.y * page_height as f64).floor().max(0.0) as u32;
+        let right = (((self.x + self.width) * page_width as f64).ceil() as u32).min(page_width);
+        let bottom = (((self.y + self.height) * page_height as f64).ceil() as u32).min(page_height);
+
+        if right <= left || bottom <= top {
+            return None;
+        }
+        Some((left, top, right - left, bottom - top))
+    }
+}
+
+pub fn region_dpi(requested: Option<u32>) -> u32 {
+    requested.unwrap_or(DEFAULT_REGION_DPI).clamp(MIN_REGION_DPI, MAX_REGION_DPI)
+}
+
+/// Re-runs OCR on a user-selected part of a page at high resolution, for small
+/// print, stamps or handwriting the full-page pass missed
+pub struct RegionOcrService {
+    file_service: FileService,
+}
+
+impl RegionOcrService {
+    pub fn new(file_service: FileService) -> Self {
+        Self { file_service }
+    }
+
+    /// Text in the region of the 1-based `page`, or None when the document has no such page
+    #[cfg(feature = "ocr")]
+    pub async fn ocr_region(
+        &self,
+        document: &Document,
+        page: u32,
+        region: PageRegion,
+        dpi: u32,
+        language: &str,
+    ) -> Result<Option<String>> {
+        use crate::ocr::page_images;
+
+        if document.mime_type.starts_with("image/") && page != 1 {
+            return Ok(None);
+        }
+
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let rendered = page_images::render_page_range(&data, &document.mime_type, dpi, page, page).await?;
+        let Some(image) = rendered.into_iter().next() else {
+            return Ok(None);
+        };
+
+        let Some((x, y, width, height)) = region.to_pixels(image.width(), image.height()) else {
+            return Ok(Some(String::new()));
+        };
+        let mut crop = image.crop_imm(x, y, width, height);
+
+        let short_side = width.min(height);
+        if short_side < MIN_CROP_SIDE {
+            let scale = MIN_CROP_SIDE as f64 / short_side as f64;
+            crop = crop.resize(
+                (width as f64 * scale).round() as u32,
+                (height as f64 * scale).round() as u32,
+                image::imageops::FilterType::Lanczos3,
+            );
+        }
+
+        tracing::debug!(
+            "OCR of {}x{} region on page {} of document {} at {} dpi",
+            width, height, page, document.id, dpi
+        );
+        page_images::ocr_image(&crop, language).await.map(Some)
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub async fn ocr_region(
+        &self,
+        _document: &Document,
+        _page: u32,
+        _region: PageRegion,
+        _dpi: u32,
+        _language: &str,
+    ) -> Result<Option<String>> {
+        anyhow::bail!("Region OCR requires OCR feature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_validation() {
+        assert!(PageRegion { x: 0.0, y: 0.0, width: 1.0, height: 1.0 }.is_valid());
+        assert!(PageRegion { x: 0.7, y: 0.9, width: 0.3, height: 0.1 }.is_valid());
+        assert!(!PageRegion { x: 0.5, y: 0.5, width: 0.0, height: 0.2 }.is_valid());
+        assert!(!PageRegion { x: -0.1, y: 0.0, width: 0.5, height: 0.5 }.is_valid());
+        assert!(!PageRegion { x: 0.8, y: 0.0, width: 0.3, height: 0.5 }.is_valid());
+        assert!(!PageRegion { x: f64::NAN, y: 0.0, width: 0.5, height: 0.5 }.is_valid());
+    }
+
+    #[test]
+    fn test_region_to_pixels() {
+        let region = PageRegion { x: 0.25, y: 0.5, width: 0.5, height: 0.25 };
+        assert_eq!(region.to_pixels(1000, 2000), Some((250, 1000, 500, 500)));
+
+        let full = PageRegion { x: 0.0, y: 0.0, width: 1.0, height: 1.0 };
+        assert_eq!(full.to_pixels(850, 1100), Some((0, 0, 850, 1100)));
+
+        assert_eq!(region.to_pixels(0, 0), None);
+        assert_eq!(region_dpi(None), DEFAULT_REGION_DPI);
+        assert_eq!(region_dpi(Some(2400)), 600);
+    }
+}
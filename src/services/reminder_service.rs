@@ -0,0 +1,106 @@
+use anyhow::Result;
+use serde_json::json;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::models::{CreateNotification, DocumentReminder};
+use crate::services::i18n;
+
+/// How often due reminders are looked for
+const POLL_SECONDS: u64 = 60;
+/// Reminders sent per run; the rest wait for the next one
+const BATCH_SIZE: i64 = 200;
+
+/// Scheduled job that turns due reminders into notifications. Notifications go out on
+/// the user's email, ntfy, Gotify and Slack channels like any other.
+pub struct ReminderService {
+    db: Database,
+}
+
+impl ReminderService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Send due reminders every minute
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(POLL_SECONDS));
+        loop {
+            interval.tick().await;
+            match self.run_once().await {
+                Ok(sent) if sent > 0 => info!("Sent {} reminder(s)", sent),
+                Ok(_) => {}
+                Err(e) => error!("Failed to send reminders: {}", e),
+            }
+        }
+    }
+
+    /// Send the reminders that are due; returns how many were sent
+    pub async fn run_once(&self) -> Result<usize> {
+        let mut sent = 0;
+        for reminder in self.db.claim_due_reminders(BATCH_SIZE).await? {
+            let locale = i18n::locale_of(&self.db, reminder.user_id).await;
+            match self.db.create_notification(reminder.user_id, &reminder_notification(&reminder, &locale)).await {
+                Ok(_) => sent += 1,
+                Err(e) => {
+                    error!("Failed to send reminder {}: {}", reminder.id, e);
+                    self.db.release_reminder(reminder.id).await?;
+                }
+            }
+        }
+        Ok(sent)
+    }
+}
+
+fn reminder_notification(reminder: &DocumentReminder, locale: &str) -> CreateNotification {
+    let notification = i18n::notification(
+        locale,
+        "info",
+        "notification.reminder",
+        &[("filename", reminder.document_filename.clone())],
+    );
+    CreateNotification {
+        message: reminder.note.clone().unwrap_or(notification.message),
+        action_url: Some(format!("/documents/{}", reminder.document_id)),
+        metadata: Some(json!({
+            "reminder_id": reminder.id,
+            "document_id": reminder.document_id,
+        })),
+        ..notification
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ReminderStatus;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_reminder_notification() {
+        let now = Utc::now();
+        let mut reminder = DocumentReminder {
+            id: Uuid::new_v4(),
+            document_id: Uuid::new_v4(),
+            document_filename: "lease.pdf".to_string(),
+            user_id: Uuid::new_v4(),
+            note: None,
+            remind_at: now,
+            status: ReminderStatus::Sent,
+            sent_at: Some(now),
+            completed_at: None,
+            snooze_count: 0,
+            created_at: now,
+            updated_at: now,
+        };
+        let notification = reminder_notification(&reminder, "en");
+        assert_eq!(notification.title, "Reminder: lease.pdf");
+        assert_eq!(notification.message, "You asked to be reminded of lease.pdf");
+        assert_eq!(notification.action_url, Some(format!("/documents/{}", reminder.document_id)));
+
+        reminder.note = Some("Cancel before the renewal".to_string());
+        assert_eq!(reminder_notification(&reminder, "en").message, "Cancel before the renewal");
+    }
+}
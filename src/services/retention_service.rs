@@ -0,0 +1,188 @@
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::{
+    models::{CreateNotification, RetentionPolicy, UserRole},
+    routes::documents::crud::remove_document_as,
+    services::audit_service::{self, document_details, AuditEvent, ClientInfo},
+    services::i18n,
+    AppState,
+};
+
+/// Label given to documents archived by a retention policy
+pub const ARCHIVE_LABEL: &str = "Archived";
+const ARCHIVE_LABEL_COLOR: &str = "#6e7781";
+
+const DEFAULT_INTERVAL_MINUTES: u64 = 60;
+/// Documents archived or deleted per policy and run, so one run stays short
+const BATCH_SIZE: i64 = 500;
+
+/// Documents handled by one run of the retention job
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RetentionRunSummary {
+    pub archived: u64,
+    pub queued_for_deletion: u64,
+    pub deleted: u64,
+}
+
+/// Scheduled job that applies retention policies: archives documents past an archive
+/// policy's retention period, puts documents past a delete policy's period in the
+/// review queue, and deletes them once their grace period ends. Every archival and
+/// deletion is recorded.
+pub struct RetentionService {
+    state: Arc<AppState>,
+}
+
+impl RetentionService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Apply the policies every `RETENTION_CHECK_INTERVAL_MINUTES` (default 60)
+    pub async fn run(self) {
+        let minutes = std::env::var("RETENTION_CHECK_INTERVAL_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+            .max(1);
+
+        let mut interval = tokio::time::interval(Duration::from_secs(minutes * 60));
+        loop {
+            interval.tick().await;
+            match self.run_once().await {
+                Ok(summary) if summary.archived + summary.queued_for_deletion + summary.deleted > 0 => info!(
+                    "Retention policies archived {} document(s), queued {} for deletion and deleted {}",
+                    summary.archived, summary.queued_for_deletion, summary.deleted
+                ),
+                Ok(_) => {}
+                Err(e) => error!("Failed to apply retention policies: {}", e),
+            }
+        }
+    }
+
+    pub async fn run_once(&self) -> Result<RetentionRunSummary> {
+        let mut summary = RetentionRunSummary::default();
+
+        for policy in self.state.db.get_enabled_retention_policies().await? {
+            let applied = match policy.action.as_str() {
+                "archive" => self.archive_due(&policy).await.map(|n| summary.archived += n),
+                "delete" => self.queue_deletions(&policy).await.map(|n| summary.queued_for_deletion += n),
+                other => {
+                    warn!("Retention policy {} has unknown action '{}'", policy.id, other);
+                    Ok(())
+                }
+            };
+            if let Err(e) = applied {
+                warn!("Failed to apply retention policy '{}' ({}): {}", policy.name, policy.id, e);
+            }
+        }
+
+        summary.deleted = self.delete_due().await?;
+        Ok(summary)
+    }
+
+    async fn archive_due(&self, policy: &RetentionPolicy) -> Result<u64> {
+        let documents = self.state.db.get_documents_due_for_archive(policy.id, BATCH_SIZE).await?;
+        if documents.is_empty() {
+            return Ok(0);
+        }
+
+        let label_id = self
+            .state
+            .db
+            .ensure_label(policy.user_id, ARCHIVE_LABEL, Some(ARCHIVE_LABEL_COLOR))
+            .await?;
+        for document in &documents {
+            self.state.db.assign_label(document.id, label_id, policy.user_id).await?;
+            self.state
+                .db
+                .record_retention_action(policy.id, &policy.name, document, "archive")
+                .await?;
+        }
+
+        info!("Retention policy '{}' archived {} document(s)", policy.name, documents.len());
+        Ok(documents.len() as u64)
+    }
+
+    async fn queue_deletions(&self, policy: &RetentionPolicy) -> Result<u64> {
+        let queued = self.state.db.queue_retention_deletions(policy.id).await?;
+        if queued == 0 {
+            return Ok(0);
+        }
+
+        let locale = i18n::locale_of(&self.state.db, policy.user_id).await;
+        let notification = CreateNotification {
+            metadata: Some(serde_json::json!({
+                "policy_id": policy.id,
+                "queued": queued,
+            })),
+            ..i18n::notification(
+                &locale,
+                "warning",
+                "notification.retention_due",
+                &[
+                    ("policy", policy.name.clone()),
+                    ("count", queued.to_string()),
+                    ("days", policy.grace_days.to_string()),
+                ],
+            )
+        };
+        if let Err(e) = self.state.db.create_notification(policy.user_id, &notification).await {
+            warn!("Failed to notify user {} of queued deletions: {}", policy.user_id, e);
+        }
+
+        Ok(queued)
+    }
+
+    async fn delete_due(&self) -> Result<u64> {
+        let mut deleted = 0;
+
+        for due in self.state.db.get_due_retention_deletions(BATCH_SIZE).await? {
+            let Some(document) = self
+                .state
+                .db
+                .get_document_by_id(due.document_id, due.user_id, UserRole::User)
+                .await?
+            else {
+                continue;
+            };
+
+            match remove_document_as(&self.state, &document, due.user_id, UserRole::User).await {
+                Ok(true) => {
+                    deleted += 1;
+                    if let Err(e) = self
+                        .state
+                        .db
+                        .record_retention_action(due.policy_id, &due.policy_name, &document, "delete")
+                        .await
+                    {
+                        error!("Failed to record retention deletion of document {}: {}", document.id, e);
+                    }
+                    audit_service::record(
+                        &self.state.db,
+                        None,
+                        &ClientInfo::default(),
+                        AuditEvent::new(audit_service::DOCUMENT_DELETE, "document", Some(document.id)).details(
+                            serde_json::json!({
+                                "policy_id": due.policy_id,
+                                "policy_name": due.policy_name,
+                                "document": document_details(&document),
+                            }),
+                        ),
+                    )
+                    .await;
+                    info!(
+                        "Retention policy '{}' deleted document {} ({})",
+                        due.policy_name, document.id, document.original_filename
+                    );
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Failed to delete document {} for retention review {}: {}", document.id, due.review_id, e),
+            }
+        }
+
+        Ok(deleted)
+    }
+}
@@ -0,0 +1,469 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+
+use crate::{
+    ingestion::document_ingestion::{DeduplicationPolicy, DocumentIngestionService, IngestionResult},
+    models::{FileIngestionInfo, S3SourceConfig, Source},
+    services::s3_service::S3Service,
+    AppState,
+};
+
+/// An object-created record from an S3 (or S3-compatible) bucket notification
+#[derive(Debug, Clone, PartialEq)]
+pub struct S3ObjectEvent {
+    pub bucket: Option<String>,
+    pub key: String,
+    pub size: i64,
+    pub etag: Option<String>,
+}
+
+/// Ingests objects named in bucket notifications, delivered by webhook or read from SQS,
+/// so new uploads do not wait for the next poll
+#[derive(Clone)]
+pub struct S3EventService {
+    state: Arc<AppState>,
+}
+
+impl S3EventService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Ingest the wanted objects among `events`, skipping keys already ingested with the
+    /// same ETag. Returns the number of new documents.
+    pub async fn ingest_events(&self, source: &Source, events: Vec<S3ObjectEvent>) -> Result<usize> {
+        let config: S3SourceConfig = serde_json::from_value(source.config.clone())
+            .map_err(|e| anyhow!("Invalid S3 config: {}", e))?;
+
+        let wanted: Vec<S3ObjectEvent> = events.into_iter().filter(|e| object_wanted(&config, e)).collect();
+        if wanted.is_empty() {
+            return Ok(0);
+        }
+
+        let s3_service = S3Service::new(config.clone()).await?;
+        let mut created = 0;
+        for event in wanted {
+            if let Some(etag) = &event.etag {
+                let seen = self.state.db.get_s3_object_etag(source.id, &event.key).await?;
+                if seen.as_deref() == Some(etag.as_str()) {
+                    debug!("S3 object {} unchanged since it was ingested, skipping", event.key);
+                    continue;
+                }
+            }
+
+            match self.ingest_object(source, &config, &s3_service, &event).await {
+                Ok(true) => created += 1,
+                Ok(false) => {}
+                Err(e) => error!("Failed to ingest S3 object {} for source '{}': {}", event.key, source.name, e),
+            }
+        }
+
+        if created > 0 {
+            info!("Ingested {} new objects from bucket notifications for source '{}'", created, source.name);
+        }
+        Ok(created)
+    }
+
+    async fn ingest_object(
+        &self,
+        source: &Source,
+        config: &S3SourceConfig,
+        s3_service: &S3Service,
+        event: &S3ObjectEvent,
+    ) -> Result<bool> {
+        let data = s3_service.download_file(&event.key).await?;
+        let name = Path::new(&event.key)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| event.key.clone());
+
+        let file_info = FileIngestionInfo {
+            relative_path: event.key.clone(),
+            full_path: format!("s3://{}/{}", config.bucket_name, event.key),
+            #[allow(deprecated)]
+            path: event.key.clone(),
+            name: name.clone(),
+            size: data.len() as i64,
+            mime_type: mime_guess::from_path(&name).first_or_octet_stream().to_string(),
+            last_modified: Some(chrono::Utc::now()),
+            etag: event.etag.clone().unwrap_or_default(),
+            is_directory: false,
+            created_at: None,
+            permissions: None,
+            owner: None,
+            group: None,
+            metadata: Some(serde_json::json!({
+                "s3_key": event.key,
+                "s3_bucket": config.bucket_name,
+                "s3_region": config.region,
+            })),
+        };
+
+        let ingestion_service =
+            DocumentIngestionService::new(self.state.db.clone(), (*self.state.file_service).clone());
+        let result = ingestion_service
+            .ingest_from_file_info(&file_info, data, source.user_id, DeduplicationPolicy::Skip, "s3_event", Some(source.id))
+            .await
+            .map_err(|e| anyhow!("Document ingestion failed for {}: {}", event.key, e))?;
+
+        let (document_id, is_new) = match result {
+            IngestionResult::Created(document) | IngestionResult::NewVersion(document) => {
+                let priority = if file_info.size <= 1024 * 1024 { 10 } else if file_info.size <= 10 * 1024 * 1024 { 6 } else { 2 };
+                if let Err(e) = self.state.queue_service.enqueue_document(document.id, priority, file_info.size).await {
+                    error!("Failed to enqueue document {} for OCR: {}", document.id, e);
+                }
+                (document.id, true)
+            }
+            IngestionResult::ExistingDocument(document) => (document.id, false),
+            IngestionResult::Skipped { existing_document_id, .. }
+            | IngestionResult::TrackedAsDuplicate { existing_document_id } => (existing_document_id, false),
+        };
+
+        self.state
+            .db
+            .record_s3_object(source.id, &event.key, event.etag.as_deref(), document_id)
+            .await?;
+        Ok(is_new)
+    }
+
+    /// Keep one SQS consumer running for every enabled S3 source with a queue configured,
+    /// picking up added, changed and removed sources every minute
+    #[cfg(feature = "s3")]
+    pub async fn run_queue_listeners(self) {
+        use std::collections::HashMap;
+        use tokio_util::sync::CancellationToken;
+
+        let mut listeners: HashMap<uuid::Uuid, (String, CancellationToken)> = HashMap::new();
+        loop {
+            match self.state.db.get_all_sources().await {
+                Ok(sources) => {
+                    let mut wanted = HashMap::new();
+                    for source in sources.into_iter().filter(|s| s.enabled && s.source_type == crate::models::SourceType::S3) {
+                        if let Some(queue_url) = sqs_queue_url(&source) {
+                            wanted.insert(source.id, queue_url);
+                        }
+                    }
+
+                    listeners.retain(|source_id, (queue_url, token)| {
+                        let keep = wanted.get(source_id) == Some(&*queue_url);
+                        if !keep {
+                            token.cancel();
+                        }
+                        keep
+                    });
+
+                    for (source_id, queue_url) in wanted {
+                        if listeners.contains_key(&source_id) {
+                            continue;
+                        }
+                        let token = CancellationToken::new();
+                        let service = self.clone();
+                        let listener_token = token.clone();
+                        crate::errors::panic::spawn_guarded(format!("S3 notification listener {}", source_id), async move {
+                            service.listen(source_id, listener_token).await;
+                        });
+                        listeners.insert(source_id, (queue_url, token));
+                    }
+                }
+                Err(e) => error!("Failed to load sources for S3 notification listeners: {}", e),
+            }
+
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        }
+    }
+
+    #[cfg(not(feature = "s3"))]
+    pub async fn run_queue_listeners(self) {
+        // SQS needs the s3 feature; webhook deliveries still work
+    }
+
+    #[cfg(feature = "s3")]
+    async fn listen(&self, source_id: uuid::Uuid, token: tokio_util::sync::CancellationToken) {
+        const RETRY_DELAY_SECONDS: u64 = 30;
+
+        while !token.is_cancelled() {
+            let received = tokio::select! {
+                _ = token.cancelled() => break,
+                received = self.receive_once(source_id) => received,
+            };
+            if let Err(e) = received {
+                tracing::warn!("S3 notification queue for source {} failed: {}", source_id, e);
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    _ = tokio::time::sleep(std::time::Duration::from_secs(RETRY_DELAY_SECONDS)) => {}
+                }
+            }
+        }
+        debug!("S3 notification listener for source {} stopped", source_id);
+    }
+
+    /// Long-poll the source's queue once and ingest what arrived. Messages are deleted once
+    /// handled; a message that fails is redelivered after its visibility timeout.
+    #[cfg(feature = "s3")]
+    async fn receive_once(&self, source_id: uuid::Uuid) -> Result<()> {
+        let source = self
+            .state
+            .db
+            .get_source_by_id(source_id)
+            .await?
+            .ok_or_else(|| anyhow!("Source {} no longer exists", source_id))?;
+        let config: S3SourceConfig = serde_json::from_value(source.config.clone())
+            .map_err(|e| anyhow!("Invalid S3 config: {}", e))?;
+        let notifications = config.notifications.clone().unwrap_or_default();
+        let queue_url = notifications
+            .sqs_queue_url
+            .clone()
+            .ok_or_else(|| anyhow!("No SQS queue configured"))?;
+
+        let client = sqs_client(&config, notifications.sqs_endpoint_url.as_deref());
+        let response = client
+            .receive_message()
+            .queue_url(&queue_url)
+            .max_number_of_messages(10)
+            .wait_time_seconds(20)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to receive from {}: {}", queue_url, e))?;
+
+        for message in response.messages.unwrap_or_default() {
+            let events = match message.body.as_deref().map(parse_notification) {
+                Some(Ok(events)) => events,
+                Some(Err(e)) => {
+                    tracing::warn!("Discarding unreadable S3 notification for source {}: {}", source_id, e);
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+
+            if let Err(e) = self.ingest_events(&source, events).await {
+                error!("Failed to handle S3 notification for source {}: {}", source_id, e);
+                continue;
+            }
+
+            if let Some(receipt_handle) = message.receipt_handle {
+                client
+                    .delete_message()
+                    .queue_url(&queue_url)
+                    .receipt_handle(receipt_handle)
+                    .send()
+                    .await
+                    .map_err(|e| anyhow!("Failed to delete handled message: {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "s3")]
+fn sqs_client(config: &S3SourceConfig, endpoint_url: Option<&str>) -> aws_sdk_sqs::Client {
+    let credentials = aws_credential_types::Credentials::new(
+        &config.access_key_id,
+        &config.secret_access_key,
+        None,
+        None,
+        "readur-s3-notifications",
+    );
+    let region = if config.region.is_empty() { "us-east-1".to_string() } else { config.region.clone() };
+
+    let mut builder = aws_sdk_sqs::config::Builder::new()
+        .region(aws_types::region::Region::new(region))
+        .credentials_provider(credentials)
+        .behavior_version_latest();
+    if let Some(endpoint_url) = endpoint_url.filter(|url| !url.is_empty()) {
+        builder = builder.endpoint_url(endpoint_url);
+    }
+
+    aws_sdk_sqs::Client::from_conf(builder.build())
+}
+
+#[cfg(feature = "s3")]
+fn sqs_queue_url(source: &Source) -> Option<String> {
+    let config: S3SourceConfig = serde_json::from_value(source.config.clone()).ok()?;
+    config
+        .notifications?
+        .sqs_queue_url
+        .filter(|url| !url.trim().is_empty())
+}
+
+/// Object-created records in an S3 event notification. Accepts the plain S3 event
+/// format (AWS, MinIO webhooks) and the SNS envelope used when SNS fans out to SQS.
+/// Test events and other event types yield no records.
+pub fn parse_notification(body: &str) -> Result<Vec<S3ObjectEvent>> {
+    let value: Value = serde_json::from_str(body)?;
+
+    if value.get("Type").and_then(Value::as_str) == Some("Notification") {
+        let message = value
+            .get("Message")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("SNS notification without a message"))?;
+        return parse_notification(message);
+    }
+
+    let Some(records) = value.get("Records").and_then(Value::as_array) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(records
+        .iter()
+        .filter_map(|record| {
+            // "ObjectCreated:Put" from AWS, "s3:ObjectCreated:Put" from MinIO
+            let event_name = record.get("eventName")?.as_str()?;
+            if !event_name.contains("ObjectCreated") {
+                return None;
+            }
+            let s3 = record.get("s3")?;
+            let object = s3.get("object")?;
+            Some(S3ObjectEvent {
+                bucket: s3.pointer("/bucket/name").and_then(Value::as_str).map(str::to_string),
+                key: decode_key(object.get("key")?.as_str()?),
+                size: object.get("size").and_then(Value::as_i64).unwrap_or(0),
+                etag: object
+                    .get("eTag")
+                    .and_then(Value::as_str)
+                    .map(|etag| etag.trim_matches('"').to_string())
+                    .filter(|etag| !etag.is_empty()),
+            })
+        })
+        .collect())
+}
+
+/// Keys in notifications are form-encoded (spaces as `+`)
+fn decode_key(raw: &str) -> String {
+    let spaced = raw.replace('+', " ");
+    urlencoding::decode(&spaced)
+        .map(|key| key.into_owned())
+        .unwrap_or(spaced)
+}
+
+/// Whether the source watches this object: same bucket, under a watched prefix,
+/// and with one of the source's extensions
+pub fn object_wanted(config: &S3SourceConfig, event: &S3ObjectEvent) -> bool {
+    if event.key.ends_with('/') {
+        return false;
+    }
+    if event.bucket.as_deref().is_some_and(|bucket| bucket != config.bucket_name) {
+        return false;
+    }
+
+    let in_watched_prefix = config.watch_folders.is_empty()
+        || config
+            .watch_folders
+            .iter()
+            .any(|folder| folder.is_empty() || folder == "/" || event.key.starts_with(folder.as_str()));
+
+    let extension = Path::new(&event.key)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    in_watched_prefix && config.file_extensions.contains(&extension)
+}
+
+/// Check a webhook's `Authorization` header against the source's secret. Accepts the
+/// secret with or without a `Bearer` prefix, since bucket webhook targets send the
+/// configured token verbatim.
+pub fn webhook_authorized(config: &S3SourceConfig, authorization: Option<&str>) -> bool {
+    let Some(secret) = config
+        .notifications
+        .as_ref()
+        .and_then(|n| n.webhook_secret.as_deref())
+        .filter(|s| !s.is_empty())
+    else {
+        return false;
+    };
+    let Some(provided) = authorization.map(|h| h.trim()) else {
+        return false;
+    };
+    let provided = provided.strip_prefix("Bearer ").unwrap_or(provided);
+
+    // Constant time so the secret cannot be recovered from response timings
+    provided.len() == secret.len()
+        && provided
+            .bytes()
+            .zip(secret.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::S3NotificationConfig;
+
+    fn config() -> S3SourceConfig {
+        S3SourceConfig {
+            bucket_name: "scans".to_string(),
+            region: "us-east-1".to_string(),
+            access_key_id: "key".to_string(),
+            secret_access_key: "secret".to_string(),
+            endpoint_url: None,
+            prefix: None,
+            watch_folders: vec!["inbox/".to_string()],
+            file_extensions: vec!["pdf".to_string()],
+            auto_sync: true,
+            sync_interval_minutes: 60,
+            notifications: Some(S3NotificationConfig {
+                sqs_queue_url: None,
+                sqs_endpoint_url: None,
+                webhook_secret: Some("s3cret".to_string()),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_parse_notification() {
+        let s3_event = r#"{"Records":[
+            {"eventName":"ObjectCreated:Put","s3":{"bucket":{"name":"scans"},"object":{"key":"inbox/Scan+2024%2B1.pdf","size":2048,"eTag":"abc123"}}},
+            {"eventName":"ObjectRemoved:Delete","s3":{"bucket":{"name":"scans"},"object":{"key":"inbox/old.pdf"}}}
+        ]}"#;
+        let events = parse_notification(s3_event).unwrap();
+        assert_eq!(
+            events,
+            vec![S3ObjectEvent {
+                bucket: Some("scans".to_string()),
+                key: "inbox/Scan 2024+1.pdf".to_string(),
+                size: 2048,
+                etag: Some("abc123".to_string()),
+            }]
+        );
+
+        let sns = serde_json::json!({ "Type": "Notification", "Message": s3_event }).to_string();
+        assert_eq!(parse_notification(&sns).unwrap(), events);
+
+        assert!(parse_notification(r#"{"Service":"Amazon S3","Event":"s3:TestEvent"}"#).unwrap().is_empty());
+        assert!(parse_notification("not json").is_err());
+    }
+
+    #[test]
+    fn test_object_wanted() {
+        let config = config();
+        let event = |bucket: &str, key: &str| S3ObjectEvent {
+            bucket: Some(bucket.to_string()),
+            key: key.to_string(),
+            size: 1,
+            etag: None,
+        };
+
+        assert!(object_wanted(&config, &event("scans", "inbox/a.PDF")));
+        assert!(!object_wanted(&config, &event("other", "inbox/a.pdf")));
+        assert!(!object_wanted(&config, &event("scans", "archive/a.pdf")));
+        assert!(!object_wanted(&config, &event("scans", "inbox/a.docx")));
+    }
+
+    #[test]
+    fn test_webhook_authorized() {
+        let mut config = config();
+        assert!(webhook_authorized(&config, Some("Bearer s3cret")));
+        assert!(webhook_authorized(&config, Some("s3cret")));
+        assert!(!webhook_authorized(&config, Some("Bearer s3cre")));
+        assert!(!webhook_authorized(&config, None));
+
+        config.notifications = None;
+        assert!(!webhook_authorized(&config, Some("Bearer s3cret")));
+    }
+}
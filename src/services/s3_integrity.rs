@@ -0,0 +1,171 @@
+//! Client-side integrity checks of S3 uploads. Every object and every part of a
+//! multipart upload is sent with its SHA-256, so the server rejects damaged bodies,
+//! and the stored object is compared with what was sent once the upload is done:
+//! its size, its number of parts and, when the server reports one, its checksum.
+//! Flaky S3-compatible setups could otherwise keep a truncated file without an error.
+
+use anyhow::{anyhow, Result};
+use base64ct::{Base64, Encoding};
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+
+/// Whether uploads carry SHA-256 checksums, from `S3_CHECKSUMS`; servers that do not
+/// support additional checksums need it off. Sizes and part counts are checked either way.
+pub static CHECKSUMS_ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("S3_CHECKSUMS")
+        .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+        .unwrap_or(true)
+});
+
+/// Files larger than this are uploaded in parts, from `S3_MULTIPART_THRESHOLD_MB`
+pub static MULTIPART_THRESHOLD: Lazy<usize> = Lazy::new(|| {
+    std::env::var("S3_MULTIPART_THRESHOLD_MB")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|mb| *mb >= 5)
+        .unwrap_or(100)
+        .saturating_mul(1024 * 1024)
+});
+
+/// SHA-256 of an object or part, raw and in the base64 form S3 uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartChecksum {
+    pub digest: [u8; 32],
+    pub base64: String,
+}
+
+impl PartChecksum {
+    pub fn of(data: &[u8]) -> Self {
+        let digest: [u8; 32] = Sha256::digest(data).into();
+        let base64 = Base64::encode_string(&digest);
+        Self { digest, base64 }
+    }
+}
+
+/// What an upload sent, to compare the stored object with
+#[derive(Debug, Clone, Default)]
+pub struct ExpectedObject {
+    pub size: u64,
+    /// Number of parts of a multipart upload, None for a single upload
+    pub parts: Option<usize>,
+    /// Checksum S3 reports for the object: the SHA-256 of the data, or for a multipart
+    /// upload the SHA-256 of the parts' digests followed by `-<parts>`
+    pub checksum: Option<String>,
+}
+
+impl ExpectedObject {
+    pub fn single(data: &[u8]) -> Self {
+        Self {
+            size: data.len() as u64,
+            parts: None,
+            checksum: CHECKSUMS_ENABLED.then(|| PartChecksum::of(data).base64),
+        }
+    }
+
+    pub fn multipart(size: u64, parts: &[PartChecksum]) -> Self {
+        Self {
+            size,
+            parts: Some(parts.len()),
+            checksum: CHECKSUMS_ENABLED.then(|| composite_checksum(parts)),
+        }
+    }
+
+    /// Compare with what a HEAD request reports of the stored object. A server that
+    /// reports no checksum is only checked for size and parts.
+    pub fn verify(&self, key: &str, size: Option<i64>, etag: Option<&str>, checksum: Option<&str>) -> Result<()> {
+        let stored_size = size.and_then(|size| u64::try_from(size).ok());
+        if stored_size != Some(self.size) {
+            return Err(anyhow!(
+                "Stored object {} has {} bytes instead of {}",
+                key,
+                stored_size.map_or_else(|| "an unknown number of".to_string(), |size| size.to_string()),
+                self.size
+            ));
+        }
+
+        // A multipart ETag ends with the number of parts; servers that compute it
+        // differently are not checked
+        if let (Some(parts), Some(etag_parts)) = (self.parts, etag.and_then(etag_part_count)) {
+            if parts != etag_parts {
+                return Err(anyhow!("Stored object {} has {} parts instead of {}", key, etag_parts, parts));
+            }
+        }
+
+        if let (Some(expected), Some(stored)) = (&self.checksum, checksum) {
+            if !checksums_match(expected, stored) {
+                return Err(anyhow!("Stored object {} has checksum {} instead of {}", key, stored, expected));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The checksum S3 gives a multipart object: the SHA-256 of the parts' digests
+pub fn composite_checksum(parts: &[PartChecksum]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.digest);
+    }
+    format!("{}-{}", Base64::encode_string(&hasher.finalize()), parts.len())
+}
+
+/// Compare checksums; some servers leave out the `-<parts>` suffix of composite ones
+fn checksums_match(expected: &str, stored: &str) -> bool {
+    let base = |checksum: &str| checksum.split('-').next().unwrap_or_default().to_string();
+    expected == stored || base(expected) == base(stored)
+}
+
+/// Number of parts in a multipart ETag such as `"9b2cf535f27731c974343645a3985328-3"`
+fn etag_part_count(etag: &str) -> Option<usize> {
+    etag.trim_matches('"').rsplit_once('-').and_then(|(_, parts)| parts.parse().ok())
+}
+
+/// Whether a part's checksum echoed by the server differs from the one sent
+pub fn part_corrupted(sent: &PartChecksum, echoed: Option<&str>) -> bool {
+    echoed.is_some_and(|echoed| echoed != sent.base64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_checksum() {
+        // SHA-256 of "abc"
+        assert_eq!(PartChecksum::of(b"abc").base64, "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=");
+        let sent = PartChecksum::of(b"abc");
+        assert!(!part_corrupted(&sent, None));
+        assert!(!part_corrupted(&sent, Some("ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=")));
+        assert!(part_corrupted(&sent, Some(&PartChecksum::of(b"ab").base64)));
+    }
+
+    #[test]
+    fn test_composite_checksum() {
+        let parts = [PartChecksum::of(b"first"), PartChecksum::of(b"second")];
+        let mut concatenated = Vec::new();
+        concatenated.extend_from_slice(&parts[0].digest);
+        concatenated.extend_from_slice(&parts[1].digest);
+        assert_eq!(composite_checksum(&parts), format!("{}-2", PartChecksum::of(&concatenated).base64));
+    }
+
+    #[test]
+    fn test_verify_size_and_parts() {
+        let expected = ExpectedObject { size: 10, parts: Some(2), checksum: None };
+        assert!(expected.verify("k", Some(10), Some("\"9b2cf535f27731c974343645a3985328-2\""), None).is_ok());
+        assert!(expected.verify("k", Some(9), None, None).is_err());
+        assert!(expected.verify("k", None, None, None).is_err());
+        assert!(expected.verify("k", Some(10), Some("\"9b2cf535f27731c974343645a3985328-3\""), None).is_err());
+        // Servers with plain ETags for multipart objects are not checked for parts
+        assert!(expected.verify("k", Some(10), Some("\"9b2cf535f27731c974343645a3985328\""), None).is_ok());
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let parts = [PartChecksum::of(b"first"), PartChecksum::of(b"second")];
+        let composite = composite_checksum(&parts);
+        let expected = ExpectedObject { size: 11, parts: Some(2), checksum: Some(composite.clone()) };
+        assert!(expected.verify("k", Some(11), None, Some(&composite)).is_ok());
+        assert!(expected.verify("k", Some(11), None, Some(composite.trim_end_matches("-2"))).is_ok());
+        assert!(expected.verify("k", Some(11), None, Some(&PartChecksum::of(b"other").base64)).is_err());
+    }
+}
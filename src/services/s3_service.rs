@@ -18,13 +18,13 @@ use aws_types::region::Region as AwsRegion;
 #[cfg(feature = "s3")]
 use aws_sdk_s3::primitives::ByteStream;
 #[cfg(feature = "s3")]
-use aws_sdk_s3::types::{CompletedPart, CompletedMultipartUpload};
+use aws_sdk_s3::types::{ChecksumAlgorithm, ChecksumMode, CompletedPart, CompletedMultipartUpload};
 
 use crate::models::{FileIngestionInfo, S3SourceConfig};
-use crate::storage::StorageBackend;
-
-/// Threshold for using streaming multipart uploads (100MB)
-const STREAMING_THRESHOLD: usize = 100 * 1024 * 1024;
+use crate::services::s3_integrity::MULTIPART_THRESHOLD;
+#[cfg(feature = "s3")]
+use crate::services::s3_integrity::{part_corrupted, ExpectedObject, PartChecksum, CHECKSUMS_ENABLED};
+use crate::storage::{FileStream, StorageBackend, BLOBS_DIR, LIBRARY_DIR};
 
 /// Multipart upload chunk size (16MB - AWS minimum is 5MB, we use 16MB for better performance)
 const MULTIPART_CHUNK_SIZE: usize = 16 * 1024 * 1024;
@@ -359,7 +359,7 @@ impl S3Service {
             let key = self.generate_document_key(user_id, document_id, filename);
             
             // Use streaming upload for large files
-            if data.len() > STREAMING_THRESHOLD {
+            if data.len() > *MULTIPART_THRESHOLD {
                 info!("Using streaming multipart upload for large file: {} ({} bytes)", key, data.len());
                 self.store_file_multipart(&key, data, None).await?;
             } else {
@@ -400,7 +400,8 @@ impl S3Service {
         }
     }
 
-    /// Generic file storage method
+    /// Generic file storage method. The object is sent with its SHA-256 and checked
+    /// once stored, so a damaged upload is sent again instead of being kept.
     async fn store_file(&self, key: &str, data: &[u8], metadata: Option<HashMap<String, String>>) -> Result<()> {
         #[cfg(not(feature = "s3"))]
         {
@@ -416,6 +417,7 @@ impl S3Service {
             let metadata_owned = metadata.clone();
             let bucket_name = self.config.bucket_name.clone();
             let client = self.client.clone();
+            let expected = ExpectedObject::single(data);
 
             self.retry_operation(&format!("store_file: {}", key), || {
                 let key = key_owned.clone();
@@ -424,6 +426,7 @@ impl S3Service {
                 let bucket_name = bucket_name.clone();
                 let client = client.clone();
                 let content_type = self.get_content_type_from_key(&key);
+                let expected = expected.clone();
 
                 async move {
                     let mut put_request = client
@@ -444,10 +447,14 @@ impl S3Service {
                         put_request = put_request.content_type(ct);
                     }
 
+                    if let Some(checksum) = &expected.checksum {
+                        put_request = put_request.checksum_sha256(checksum);
+                    }
+
                     put_request.send().await
                         .map_err(|e| anyhow!("Failed to store file {}: {}", key, e))?;
 
-                    Ok(())
+                    self.verify_stored_object(&key, &expected).await
                 }
             }).await?;
 
@@ -467,124 +474,236 @@ impl S3Service {
         {
             info!("Starting multipart upload for file: {}/{} ({} bytes)", self.config.bucket_name, key, data.len());
 
-            let key_owned = key.to_string();
-            let data_owned = data.to_vec();
-            let metadata_owned = metadata.clone();
-            let bucket_name = self.config.bucket_name.clone();
-            let client = self.client.clone();
-
-            self.retry_operation(&format!("store_file_multipart: {}", key), || {
-                let key = key_owned.clone();
-                let data = data_owned.clone();
-                let metadata = metadata_owned.clone();
-                let bucket_name = bucket_name.clone();
-                let client = client.clone();
-                let content_type = self.get_content_type_from_key(&key);
+            let upload_id = self.create_multipart_upload(key, metadata).await?;
+            let total_chunks = data.len().div_ceil(MULTIPART_CHUNK_SIZE);
+            let mut completed_parts = Vec::with_capacity(total_chunks);
+            let mut checksums = Vec::with_capacity(total_chunks);
 
-                async move {
-                    // Step 1: Initiate multipart upload
-                    let mut create_request = client
-                        .create_multipart_upload()
-                        .bucket(&bucket_name)
-                        .key(&key);
+            for (chunk_index, chunk) in data.chunks(MULTIPART_CHUNK_SIZE).enumerate() {
+                let part_number = (chunk_index + 1) as i32;
+                debug!("Uploading part {} of {} for {} ({} bytes)", part_number, total_chunks, key, chunk.len());
 
-                    // Add metadata if provided
-                    if let Some(meta) = metadata {
-                        for (k, v) in meta {
-                            create_request = create_request.metadata(k, v);
-                        }
+                match self.upload_part(key, &upload_id, part_number, chunk.to_vec()).await {
+                    Ok((part, checksum)) => {
+                        completed_parts.push(part);
+                        checksums.push(checksum);
                     }
-
-                    // Set content type based on file extension
-                    if let Some(ct) = content_type {
-                        create_request = create_request.content_type(ct);
+                    Err(e) => {
+                        self.abort_multipart_upload(key, &upload_id).await;
+                        return Err(e);
                     }
+                }
+            }
 
-                    let create_response = create_request.send().await
-                        .map_err(|e| anyhow!("Failed to initiate multipart upload for {}: {}", key, e))?;
-                    
-                    let upload_id = create_response.upload_id()
-                        .ok_or_else(|| anyhow!("Missing upload ID in multipart upload response"))?;
-                    
-                    info!("Initiated multipart upload for {}: {}", key, upload_id);
+            let expected = ExpectedObject::multipart(data.len() as u64, &checksums);
+            self.complete_multipart_upload(key, &upload_id, completed_parts, &expected).await
+        }
+    }
 
-                    // Step 2: Upload parts in chunks
-                    let mut completed_parts = Vec::new();
-                    let total_chunks = (data.len() + MULTIPART_CHUNK_SIZE - 1) / MULTIPART_CHUNK_SIZE;
-                    
-                    for (chunk_index, chunk) in data.chunks(MULTIPART_CHUNK_SIZE).enumerate() {
-                        let part_number = (chunk_index + 1) as i32;
-                        
-                        debug!("Uploading part {} of {} for {} ({} bytes)", 
-                               part_number, total_chunks, key, chunk.len());
-
-                        let upload_part_response = client
-                            .upload_part()
-                            .bucket(&bucket_name)
-                            .key(&key)
-                            .upload_id(upload_id)
-                            .part_number(part_number)
-                            .body(ByteStream::from(chunk.to_vec()))
-                            .send()
-                            .await
-                            .map_err(|e| anyhow!("Failed to upload part {} for {}: {}", part_number, key, e))?;
-
-                        let etag = upload_part_response.e_tag()
-                            .ok_or_else(|| anyhow!("Missing ETag in upload part response"))?;
-
-                        completed_parts.push(
-                            CompletedPart::builder()
-                                .part_number(part_number)
-                                .e_tag(etag)
-                                .build()
-                        );
-                        
-                        debug!("Successfully uploaded part {} for {}", part_number, key);
-                    }
+    /// Upload a local file under `key`, reading large files one part at a time instead
+    /// of into memory. Returns the number of bytes uploaded.
+    pub async fn store_file_from_path(&self, key: &str, path: &std::path::Path) -> Result<u64> {
+        #[cfg(not(feature = "s3"))]
+        {
+            return Err(anyhow!("S3 support not compiled in"));
+        }
 
-                    // Step 3: Complete multipart upload
-                    let completed_multipart_upload = CompletedMultipartUpload::builder()
-                        .set_parts(Some(completed_parts))
-                        .build();
+        #[cfg(feature = "s3")]
+        {
+            let size = tokio::fs::metadata(path).await?.len();
+            if size <= *MULTIPART_THRESHOLD as u64 {
+                let data = tokio::fs::read(path).await?;
+                self.store_file(key, &data, None).await?;
+                return Ok(size);
+            }
 
-                    client
-                        .complete_multipart_upload()
-                        .bucket(&bucket_name)
-                        .key(&key)
-                        .upload_id(upload_id)
-                        .multipart_upload(completed_multipart_upload)
-                        .send()
-                        .await
-                        .map_err(|e| {
-                            // If completion fails, try to abort the multipart upload
-                            let abort_client = client.clone();
-                            let abort_bucket = bucket_name.clone();
-                            let abort_key = key.clone();
-                            let abort_upload_id = upload_id.to_string();
-                            
-                            tokio::spawn(async move {
-                                if let Err(abort_err) = abort_client
-                                    .abort_multipart_upload()
-                                    .bucket(abort_bucket)
-                                    .key(abort_key)
-                                    .upload_id(abort_upload_id)
-                                    .send()
-                                    .await
-                                {
-                                    error!("Failed to abort multipart upload: {}", abort_err);
-                                }
-                            });
-                            
-                            anyhow!("Failed to complete multipart upload for {}: {}", key, e)
-                        })?;
+            info!("Starting multipart upload of {} to {}/{} ({} bytes)", path.display(), self.config.bucket_name, key, size);
+            let upload_id = self.create_multipart_upload(key, None).await?;
 
-                    info!("Successfully completed multipart upload for {}", key);
-                    Ok(())
+            match self.upload_parts_from_path(key, &upload_id, path).await {
+                Ok((completed_parts, checksums)) => {
+                    let expected = ExpectedObject::multipart(size, &checksums);
+                    self.complete_multipart_upload(key, &upload_id, completed_parts, &expected).await?;
+                    Ok(size)
                 }
-            }).await?;
+                Err(e) => {
+                    self.abort_multipart_upload(key, &upload_id).await;
+                    Err(e)
+                }
+            }
+        }
+    }
 
-            Ok(())
+    #[cfg(feature = "s3")]
+    async fn upload_parts_from_path(&self, key: &str, upload_id: &str, path: &std::path::Path) -> Result<(Vec<CompletedPart>, Vec<PartChecksum>)> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut completed_parts = Vec::new();
+        let mut checksums = Vec::new();
+
+        loop {
+            let mut chunk = vec![0u8; MULTIPART_CHUNK_SIZE];
+            let mut filled = 0;
+            while filled < chunk.len() {
+                let read = file.read(&mut chunk[filled..]).await?;
+                if read == 0 {
+                    break;
+                }
+                filled += read;
+            }
+            if filled == 0 {
+                break;
+            }
+            chunk.truncate(filled);
+
+            let part_number = completed_parts.len() as i32 + 1;
+            debug!("Uploading part {} for {} ({} bytes)", part_number, key, chunk.len());
+            let (part, checksum) = self.upload_part(key, upload_id, part_number, chunk).await?;
+            completed_parts.push(part);
+            checksums.push(checksum);
+            if filled < MULTIPART_CHUNK_SIZE {
+                break;
+            }
+        }
+
+        Ok((completed_parts, checksums))
+    }
+
+    #[cfg(feature = "s3")]
+    async fn create_multipart_upload(&self, key: &str, metadata: Option<HashMap<String, String>>) -> Result<String> {
+        let mut create_request = self.client
+            .create_multipart_upload()
+            .bucket(&self.config.bucket_name)
+            .key(key);
+
+        // Add metadata if provided
+        if let Some(meta) = metadata {
+            for (k, v) in meta {
+                create_request = create_request.metadata(k, v);
+            }
+        }
+
+        // Set content type based on file extension
+        if let Some(ct) = self.get_content_type_from_key(key) {
+            create_request = create_request.content_type(ct);
+        }
+
+        if *CHECKSUMS_ENABLED {
+            create_request = create_request.checksum_algorithm(ChecksumAlgorithm::Sha256);
+        }
+
+        let create_response = create_request.send().await
+            .map_err(|e| anyhow!("Failed to initiate multipart upload for {}: {}", key, e))?;
+        let upload_id = create_response.upload_id()
+            .ok_or_else(|| anyhow!("Missing upload ID in multipart upload response"))?;
+
+        info!("Initiated multipart upload for {}: {}", key, upload_id);
+        Ok(upload_id.to_string())
+    }
+
+    /// Upload one part with its SHA-256, sending it again when the server rejects it
+    /// or echoes a different checksum
+    #[cfg(feature = "s3")]
+    async fn upload_part(&self, key: &str, upload_id: &str, part_number: i32, chunk: Vec<u8>) -> Result<(CompletedPart, PartChecksum)> {
+        let checksum = PartChecksum::of(&chunk);
+
+        let part = self.retry_operation(&format!("upload_part {}: {}", part_number, key), || {
+            let chunk = chunk.clone();
+            let checksum = &checksum;
+            async move {
+                let mut request = self.client
+                    .upload_part()
+                    .bucket(&self.config.bucket_name)
+                    .key(key)
+                    .upload_id(upload_id)
+                    .part_number(part_number)
+                    .body(ByteStream::from(chunk));
+                if *CHECKSUMS_ENABLED {
+                    request = request.checksum_sha256(&checksum.base64);
+                }
+
+                let response = request.send()
+                    .await
+                    .map_err(|e| anyhow!("Failed to upload part {} for {}: {}", part_number, key, e))?;
+                if part_corrupted(checksum, response.checksum_sha256()) {
+                    return Err(anyhow!("Part {} of {} was stored with a different checksum", part_number, key));
+                }
+                let etag = response.e_tag()
+                    .ok_or_else(|| anyhow!("Missing ETag in upload part response"))?;
+
+                let mut part = CompletedPart::builder().part_number(part_number).e_tag(etag);
+                if *CHECKSUMS_ENABLED {
+                    part = part.checksum_sha256(&checksum.base64);
+                }
+                Ok(part.build())
+            }
+        }).await?;
+
+        Ok((part, checksum))
+    }
+
+    /// Complete a multipart upload and check the stored object. An object that does not
+    /// match what was sent is deleted rather than kept.
+    #[cfg(feature = "s3")]
+    async fn complete_multipart_upload(&self, key: &str, upload_id: &str, completed_parts: Vec<CompletedPart>, expected: &ExpectedObject) -> Result<()> {
+        if let Err(e) = self.client
+            .complete_multipart_upload()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(completed_parts)).build())
+            .send()
+            .await
+        {
+            self.abort_multipart_upload(key, upload_id).await;
+            return Err(anyhow!("Failed to complete multipart upload for {}: {}", key, e));
+        }
+
+        self.verify_stored_object(key, expected).await?;
+        info!("Successfully completed multipart upload for {}", key);
+        Ok(())
+    }
+
+    #[cfg(feature = "s3")]
+    async fn abort_multipart_upload(&self, key: &str, upload_id: &str) {
+        if let Err(abort_err) = self.client
+            .abort_multipart_upload()
+            .bucket(&self.config.bucket_name)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await
+        {
+            error!("Failed to abort multipart upload: {}", abort_err);
+        }
+    }
+
+    /// Compare a stored object with what was uploaded, deleting it when they differ
+    #[cfg(feature = "s3")]
+    async fn verify_stored_object(&self, key: &str, expected: &ExpectedObject) -> Result<()> {
+        let mut head_request = self.client
+            .head_object()
+            .bucket(&self.config.bucket_name)
+            .key(key);
+        if expected.checksum.is_some() {
+            head_request = head_request.checksum_mode(ChecksumMode::Enabled);
+        }
+        let head = head_request.send().await
+            .map_err(|e| anyhow!("Failed to check stored object {}: {}", key, e))?;
+
+        if let Err(e) = expected.verify(key, head.content_length(), head.e_tag(), head.checksum_sha256()) {
+            error!("Integrity check failed, removing {}: {}", key, e);
+            if let Err(delete_err) = self.client
+                .delete_object()
+                .bucket(&self.config.bucket_name)
+                .key(key)
+                .send()
+                .await
+            {
+                error!("Failed to remove corrupted object {}: {}", key, delete_err);
+            }
+            return Err(e);
         }
+        Ok(())
     }
 
     /// Retrieve a file from S3
@@ -817,7 +936,7 @@ impl StorageBackend for S3Service {
         let key = self.generate_document_key(user_id, document_id, filename);
         
         // Use streaming upload for large files
-        if data.len() > STREAMING_THRESHOLD {
+        if data.len() > *MULTIPART_THRESHOLD {
             info!("Using streaming multipart upload for large file: {} ({} bytes)", key, data.len());
             self.store_file_multipart(&key, data, None).await?;
         } else {
@@ -886,6 +1005,98 @@ impl StorageBackend for S3Service {
         }
     }
 
+    async fn store_document_from_path(&self, user_id: Uuid, document_id: Uuid, filename: &str, source: &std::path::Path) -> Result<String> {
+        let key = self.generate_document_key(user_id, document_id, filename);
+        self.store_file_from_path(&key, source).await?;
+        Ok(format!("s3://{}", key))
+    }
+
+    async fn retrieve_stream(&self, path: &str) -> Result<FileStream> {
+        let key = path.strip_prefix("s3://").unwrap_or(path);
+
+        #[cfg(not(feature = "s3"))]
+        {
+            return Err(anyhow!("S3 support not compiled in"));
+        }
+
+        #[cfg(feature = "s3")]
+        {
+            let response = self.client
+                .get_object()
+                .bucket(&self.config.bucket_name)
+                .key(key)
+                .send()
+                .await
+                .map_err(|e| anyhow!("Failed to retrieve file {}: {}", key, e))?;
+
+            let size = response.content_length().and_then(|length| u64::try_from(length).ok());
+            let chunks = futures::stream::unfold(response.body, |mut body| async move {
+                body.next().await.map(|chunk| (chunk.map_err(std::io::Error::other), body))
+            });
+
+            Ok(FileStream { size, chunks: Box::pin(chunks) })
+        }
+    }
+
+    async fn store_content_blob(&self, key: &str, data: &[u8]) -> Result<String> {
+        let key = format!("{}/{}", BLOBS_DIR, key);
+        if data.len() > *MULTIPART_THRESHOLD {
+            self.store_file_multipart(&key, data, None).await?;
+        } else {
+            self.store_file(&key, data, None).await?;
+        }
+
+        Ok(format!("s3://{}", key))
+    }
+
+    async fn delete_content_blob(&self, path: &str) -> Result<()> {
+        self.delete_file(path.strip_prefix("s3://").unwrap_or(path)).await
+    }
+
+    async fn store_library_file(&self, relative_path: &str, data: &[u8]) -> Result<String> {
+        let key = format!("{}/{}", LIBRARY_DIR, relative_path);
+        if data.len() > *MULTIPART_THRESHOLD {
+            self.store_file_multipart(&key, data, None).await?;
+        } else {
+            self.store_file(&key, data, None).await?;
+        }
+
+        Ok(format!("s3://{}", key))
+    }
+
+    async fn delete_stored_file(&self, path: &str) -> Result<()> {
+        self.delete_file(path.strip_prefix("s3://").unwrap_or(path)).await
+    }
+
+    async fn retrieve_range_stream(&self, path: &str, start: u64, end: u64) -> Result<FileStream> {
+        let key = path.strip_prefix("s3://").unwrap_or(path);
+
+        #[cfg(not(feature = "s3"))]
+        {
+            let _ = (start, end);
+            return Err(anyhow!("S3 support not compiled in"));
+        }
+
+        #[cfg(feature = "s3")]
+        {
+            let response = self.client
+                .get_object()
+                .bucket(&self.config.bucket_name)
+                .key(key)
+                .range(format!("bytes={}-{}", start, end))
+                .send()
+                .await
+                .map_err(|e| anyhow!("Failed to retrieve bytes {}-{} of {}: {}", start, end, key, e))?;
+
+            let size = response.content_length().and_then(|length| u64::try_from(length).ok());
+            let chunks = futures::stream::unfold(response.body, |mut body| async move {
+                body.next().await.map(|chunk| (chunk.map_err(std::io::Error::other), body))
+            });
+
+            Ok(FileStream { size, chunks: Box::pin(chunks) })
+        }
+    }
+
     async fn delete_document_files(&self, user_id: Uuid, document_id: Uuid, filename: &str) -> Result<()> {
         #[cfg(not(feature = "s3"))]
         {
@@ -993,6 +1204,7 @@ mod tests {
             file_extensions: vec!["pdf".to_string(), "txt".to_string()],
             auto_sync: true,
             sync_interval_minutes: 60,
+            notifications: None,
         };
 
         // This will create the client but won't test actual S3 access
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::db::Database;
+use crate::models::{CreateNotification, Document, SavedSearch};
+use crate::services::i18n;
+
+/// Longest saved search name
+pub const MAX_NAME_LENGTH: usize = 100;
+
+/// Checks freshly processed documents against the saved searches that asked to be
+/// notified and tells their owners about every match
+pub struct SavedSearchService {
+    db: Database,
+}
+
+impl SavedSearchService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Notify the owner of every notifying saved search the document matches. Only
+    /// searches whose owner can see the document (own or shared) are considered.
+    /// Returns the number of notifications created.
+    pub async fn notify_matches(&self, document: &Document) -> Result<usize> {
+        let saved_searches = self.db.get_notifying_saved_searches().await?;
+
+        let mut notified = 0;
+        for saved_search in &saved_searches {
+            let request = saved_search.search.to_search_request(None, None);
+            match self
+                .db
+                .document_matches_search(document.id, saved_search.user_id, &request)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    warn!(
+                        "Failed to match document {} against saved search {}: {}",
+                        document.id, saved_search.id, e
+                    );
+                    continue;
+                }
+            }
+
+            let locale = i18n::locale_of(&self.db, saved_search.user_id).await;
+            self.db
+                .create_notification(saved_search.user_id, &match_notification(saved_search, document, &locale))
+                .await?;
+            self.db.touch_saved_search_match(saved_search.id).await?;
+            notified += 1;
+        }
+
+        if notified > 0 {
+            info!("Document {} matched {} saved search(es)", document.id, notified);
+        }
+        Ok(notified)
+    }
+}
+
+fn match_notification(saved_search: &SavedSearch, document: &Document, locale: &str) -> CreateNotification {
+    CreateNotification {
+        action_url: Some(format!("/documents/{}", document.id)),
+        metadata: Some(json!({
+            "saved_search_id": saved_search.id,
+            "document_id": document.id,
+        })),
+        ..i18n::notification(
+            locale,
+            "info",
+            "notification.saved_search_match",
+            &[("filename", document.original_filename.clone()), ("search", saved_search.name.clone())],
+        )
+    }
+}
+
+/// A trimmed saved search name, or None when it is empty or too long
+pub fn normalize_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return None;
+    }
+    Some(name.to_string())
+}
@@ -0,0 +1,143 @@
+//! Pairing and pushing for desktop bridge apps, which drive a scanner attached to the
+//! computer they run on (TWAIN, WIA or SANE) and push the scanned pages to readur.
+//!
+//! Pairing follows the OAuth device flow: the bridge starts a pairing and shows a short
+//! code, the user approves the code in readur, and the bridge, polling with the secret
+//! device code, collects a token of its own. The token only works for the push API.
+
+use rand::{rngs::OsRng, Rng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::models::ScanPushMetadata;
+use crate::routes::documents::types::{CaptureMetadata, CapturePageMetadata};
+
+/// Marks bridge tokens, so they are not mistaken for API tokens or JWTs
+pub const SCAN_BRIDGE_TOKEN_PREFIX: &str = "rdrb_";
+/// Characters of a token kept in the clear to recognise it
+const TOKEN_DISPLAY_LENGTH: usize = 13;
+/// Minutes a pairing code can be approved and collected in
+pub const PAIRING_MINUTES: i64 = 10;
+/// Seconds a bridge waits between polls for its token
+pub const POLL_INTERVAL_SECONDS: u64 = 5;
+/// Page of the web app pairing codes are entered on
+pub const VERIFICATION_PATH: &str = "/settings/scan-bridges";
+/// Pages one push may have; an automatic document feeder batch is usually far less
+pub const MAX_PUSH_PAGES: usize = 200;
+/// Bridges one user may pair
+pub const MAX_BRIDGES_PER_USER: i64 = 50;
+/// No 0/O or 1/I, so codes read aloud or typed from a screen come out right
+const USER_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const USER_CODE_LENGTH: usize = 8;
+
+/// A new device code for a bridge to poll with
+pub fn generate_device_code() -> String {
+    random_hex()
+}
+
+/// A new pairing code in the form `XXXX-XXXX`
+pub fn generate_user_code() -> String {
+    let mut rng = OsRng;
+    let code: String = (0..USER_CODE_LENGTH)
+        .map(|_| USER_CODE_ALPHABET[rng.gen_range(0..USER_CODE_ALPHABET.len())] as char)
+        .collect();
+    format!("{}-{}", &code[..USER_CODE_LENGTH / 2], &code[USER_CODE_LENGTH / 2..])
+}
+
+/// A pairing code as typed by the user, in the stored form; None when it cannot be one
+pub fn normalize_user_code(code: &str) -> Option<String> {
+    let code: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if code.len() != USER_CODE_LENGTH || !code.bytes().all(|b| USER_CODE_ALPHABET.contains(&b)) {
+        return None;
+    }
+    Some(format!("{}-{}", &code[..USER_CODE_LENGTH / 2], &code[USER_CODE_LENGTH / 2..]))
+}
+
+/// A new bridge token and the prefix shown in bridge lists
+pub fn generate_token() -> (String, String) {
+    let token = format!("{}{}", SCAN_BRIDGE_TOKEN_PREFIX, random_hex());
+    let prefix = token[..TOKEN_DISPLAY_LENGTH].to_string();
+    (token, prefix)
+}
+
+/// Device codes and tokens are long and random, so a plain hash protects them like API
+/// tokens
+pub fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn random_hex() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// How to assemble pushed pages: scanned pages are already flat and evenly lit, so they
+/// are only turned, never cut out or given more contrast
+pub fn capture_metadata(metadata: &ScanPushMetadata) -> CaptureMetadata {
+    CaptureMetadata {
+        pages: metadata
+            .rotations
+            .iter()
+            .map(|&rotation| CapturePageMetadata { corners: None, rotation })
+            .collect(),
+        device: None,
+        captured_at: metadata.scanned_at,
+        filename: Some(scan_filename(metadata.filename.as_deref(), metadata.scanned_at.unwrap_or_else(chrono::Utc::now))),
+        enhance: Some(false),
+    }
+}
+
+/// The push's file name, with a `.pdf` extension
+fn scan_filename(requested: Option<&str>, scanned_at: chrono::DateTime<chrono::Utc>) -> String {
+    let name = requested
+        .map(|name| name.trim().replace(['/', '\\'], "-"))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("scan-{}", scanned_at.format("%Y%m%d-%H%M%S")));
+    if name.to_ascii_lowercase().ends_with(".pdf") {
+        name
+    } else {
+        format!("{}.pdf", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_user_codes() {
+        let code = generate_user_code();
+        assert_eq!(code.len(), 9);
+        assert_eq!(normalize_user_code(&code), Some(code.clone()));
+        assert_eq!(normalize_user_code(&code.to_lowercase().replace('-', " ")), Some(code));
+        assert_eq!(normalize_user_code("kxpm7qrt"), Some("KXPM-7QRT".to_string()));
+        assert_eq!(normalize_user_code("KXPM-7QR"), None);
+        assert_eq!(normalize_user_code("KXPM-7QR0"), None);
+    }
+
+    #[test]
+    fn test_generate_token() {
+        let (token, prefix) = generate_token();
+        assert!(token.starts_with(SCAN_BRIDGE_TOKEN_PREFIX));
+        assert!(!token.starts_with(crate::auth::API_TOKEN_PREFIX));
+        assert!(token.starts_with(&prefix));
+        assert_ne!(hash_secret(&token), hash_secret(&generate_token().0));
+    }
+
+    #[test]
+    fn test_capture_metadata() {
+        let scanned_at = chrono::Utc.with_ymd_and_hms(2025, 10, 15, 9, 30, 5).unwrap();
+        let metadata = ScanPushMetadata { scanned_at: Some(scanned_at), rotations: vec![0, 180], ..Default::default() };
+        let capture = capture_metadata(&metadata);
+        assert_eq!(capture.filename.as_deref(), Some("scan-20251015-093005.pdf"));
+        assert_eq!(capture.enhance, Some(false));
+        assert_eq!(capture.pages[1].rotation, 180);
+        assert!(capture.pages.iter().all(|page| page.corners.is_none()));
+        assert_eq!(scan_filename(Some("Invoices/October"), scanned_at), "Invoices-October.pdf");
+    }
+}
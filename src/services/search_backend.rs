@@ -0,0 +1,211 @@
+//! The search layer behind the search endpoints. A search is answered by the first
+//! backend that can: the external engine when it is enabled for queries, and Postgres
+//! full-text search, which answers every search, otherwise.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{Document, EnhancedDocumentResponse, SearchMode, SearchRequest, UserRole};
+use crate::services::external_search::{self, EngineQuery, ExternalSearch};
+use crate::utils::search_query;
+
+/// A search as an endpoint received it
+#[derive(Debug, Clone, Copy)]
+pub struct SearchQuery<'a> {
+    pub user_id: Uuid,
+    /// Admins search everyone's documents
+    pub role: UserRole,
+    pub request: &'a SearchRequest,
+    pub limit: i64,
+    pub offset: i64,
+    /// Rank the matches and add snippets, as `/api/search/enhanced` does; otherwise
+    /// matches are listed newest first
+    pub enhanced: bool,
+}
+
+impl SearchQuery<'_> {
+    /// The user whose documents are searched, None for all documents
+    fn owner(&self) -> Option<Uuid> {
+        (self.role != UserRole::Admin).then_some(self.user_id)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SearchResults {
+    pub documents: Vec<EnhancedDocumentResponse>,
+    /// Total matches, which external engines may estimate
+    pub total: i64,
+    /// Nothing matched the words as typed and the documents were found by fuzzy
+    /// matching instead
+    pub fuzzy_fallback: bool,
+}
+
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Name recorded with the search in the slow operation profiler
+    fn name(&self) -> &'static str;
+
+    /// The matching documents, or None when the backend cannot answer this search and
+    /// the next one has to
+    async fn search(&self, query: &SearchQuery<'_>) -> Result<Option<SearchResults>>;
+}
+
+/// The backends in the order they are asked, ending with Postgres
+pub fn backends(db: &Database) -> Vec<Box<dyn SearchBackend>> {
+    let mut backends: Vec<Box<dyn SearchBackend>> = Vec::new();
+    if let Some(search) = external_search::configured().filter(|search| search.config.query_enabled) {
+        backends.push(Box::new(ExternalBackend { db: db.clone(), search }));
+    }
+    backends.push(Box::new(PostgresBackend { db: db.clone() }));
+    backends
+}
+
+/// Answer a search from the first backend that can, with the name of that backend
+pub async fn search(db: &Database, query: &SearchQuery<'_>) -> (&'static str, Result<SearchResults>) {
+    for backend in backends(db) {
+        match backend.search(query).await {
+            Ok(None) => continue,
+            Ok(Some(results)) => return (backend.name(), Ok(results)),
+            Err(e) => return (backend.name(), Err(e)),
+        }
+    }
+    ("none", Err(anyhow!("No search backend could answer the search")))
+}
+
+/// Postgres full-text search over the documents table, which indexes documents as they
+/// are written
+pub struct PostgresBackend {
+    db: Database,
+}
+
+#[async_trait]
+impl SearchBackend for PostgresBackend {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn search(&self, query: &SearchQuery<'_>) -> Result<Option<SearchResults>> {
+        if !query.enhanced {
+            let documents = self.db.search_documents(query.user_id, query.request).await?;
+            let total = documents.len() as i64;
+            return Ok(Some(SearchResults {
+                documents: documents.into_iter().map(document_response).collect(),
+                total,
+                fuzzy_fallback: false,
+            }));
+        }
+
+        let mut documents = self
+            .db
+            .enhanced_search_documents_with_role(query.user_id, query.role, query.request)
+            .await?;
+
+        // Nothing matches the words as typed: fall back to trigram matching, which
+        // tolerates misspellings and OCR errors
+        let simple_mode = matches!(query.request.search_mode, None | Some(SearchMode::Simple));
+        let plain_words = simple_mode
+            && search_query::parse(&query.request.query).is_ok_and(|parsed| parsed.is_plain_text())
+            && !query.request.query.trim().is_empty();
+        let fuzzy_fallback = documents.is_empty() && query.offset == 0 && plain_words;
+        if fuzzy_fallback {
+            let fuzzy_request = SearchRequest { search_mode: Some(SearchMode::Fuzzy), ..query.request.clone() };
+            documents = self
+                .db
+                .enhanced_search_documents_with_role(query.user_id, query.role, &fuzzy_request)
+                .await?;
+        }
+
+        let total = documents.len() as i64;
+        Ok(Some(SearchResults { documents, total, fuzzy_fallback }))
+    }
+}
+
+/// The external engine configured for queries
+pub struct ExternalBackend {
+    db: Database,
+    search: Arc<ExternalSearch>,
+}
+
+#[async_trait]
+impl SearchBackend for ExternalBackend {
+    fn name(&self) -> &'static str {
+        "external"
+    }
+
+    /// None leaves the search to Postgres: the engine cannot apply the filters, or failed
+    async fn search(&self, query: &SearchQuery<'_>) -> Result<Option<SearchResults>> {
+        let request = query.request;
+        // Field filters, phrases and operators of the query language are only understood by Postgres
+        if !search_query::parse(&request.query).is_ok_and(|parsed| parsed.is_plain_text()) {
+            return Ok(None);
+        }
+        // Coordinates are not indexed
+        if request.near.is_some() || request.bbox.is_some() {
+            return Ok(None);
+        }
+        let tags = request.tags.as_deref().unwrap_or_default();
+        let mime_types = request.mime_types.as_deref().unwrap_or_default();
+        if !self.search.can_filter(!tags.is_empty(), !mime_types.is_empty()) {
+            return Ok(None);
+        }
+        // The index only knows owners, so searches that must include shared documents go to Postgres
+        let owner = query.owner();
+        if let Some(user_id) = owner {
+            if self.db.has_shared_documents(user_id).await.unwrap_or(true) {
+                return Ok(None);
+            }
+        }
+
+        let engine_query =
+            EngineQuery { text: &request.query, user_id: owner, tags, mime_types, limit: query.limit, offset: query.offset };
+        let hits = match self.search.client.search(&engine_query).await {
+            Ok(hits) => hits,
+            Err(e) => {
+                warn!("External search failed, falling back to Postgres: {}", e);
+                return Ok(None);
+            }
+        };
+        let documents = match self.db.get_documents_by_ids(&hits.document_ids).await {
+            Ok(documents) => documents,
+            Err(e) => {
+                warn!("Failed to load external search hits, falling back to Postgres: {}", e);
+                return Ok(None);
+            }
+        };
+
+        // The index can trail deletes by a poll interval, so hits are checked against the database
+        let mut by_id: HashMap<Uuid, Document> = documents
+            .into_iter()
+            .filter(|doc| owner.is_none() || owner == Some(doc.user_id))
+            .map(|doc| (doc.id, doc))
+            .collect();
+        let documents = hits.document_ids.iter().filter_map(|id| by_id.remove(id)).map(document_response).collect();
+
+        Ok(Some(SearchResults { documents, total: hits.total, fuzzy_fallback: false }))
+    }
+}
+
+fn document_response(doc: Document) -> EnhancedDocumentResponse {
+    EnhancedDocumentResponse {
+        id: doc.id,
+        filename: doc.filename,
+        original_filename: doc.original_filename,
+        file_size: doc.file_size,
+        mime_type: doc.mime_type,
+        tags: doc.tags,
+        created_at: doc.created_at,
+        has_ocr_text: doc.ocr_text.is_some(),
+        ocr_confidence: doc.ocr_confidence,
+        ocr_word_count: doc.ocr_word_count,
+        ocr_processing_time_ms: doc.ocr_processing_time_ms,
+        ocr_status: doc.ocr_status,
+        search_rank: None,
+        snippets: Vec::new(),
+        matched_fields: Vec::new(),
+    }
+}
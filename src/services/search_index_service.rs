@@ -0,0 +1,101 @@
+//! Rebuilds of the full-text index, e.g. after an admin changes how a language is
+//! analyzed. Rebuilds run in the background in id order, record their progress after
+//! every batch and continue after a restart.
+
+use anyhow::Result;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::errors::panic::spawn_guarded;
+use crate::models::{SearchAnalyzer, SearchIndexRebuild, SEARCH_LANGUAGES};
+
+/// Documents reindexed per transaction
+const BATCH_SIZE: i64 = 500;
+
+#[derive(Clone)]
+pub struct SearchIndexService {
+    db: Database,
+}
+
+impl SearchIndexService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// The analyzer of every search language, built-in ones included
+    pub async fn analyzers(&self) -> Result<Vec<SearchAnalyzer>> {
+        let mut configured = self.db.list_search_analyzers().await?;
+        Ok(SEARCH_LANGUAGES
+            .iter()
+            .map(|language| match configured.iter().position(|a| a.language == *language) {
+                Some(index) => configured.swap_remove(index),
+                None => SearchAnalyzer::default_for(language),
+            })
+            .collect())
+    }
+
+    /// Start rebuilding the index of the documents indexed in `language`, or of every
+    /// document. None while another rebuild is running.
+    pub async fn start_rebuild(&self, language: Option<&str>, started_by: Uuid) -> Result<Option<SearchIndexRebuild>> {
+        let Some(rebuild) = self.db.start_search_index_rebuild(language, started_by).await? else {
+            return Ok(None);
+        };
+        info!(
+            "Rebuilding the search index of {} documents ({})",
+            rebuild.total_documents,
+            language.unwrap_or("all languages")
+        );
+        self.spawn(rebuild.clone());
+        Ok(Some(rebuild))
+    }
+
+    /// Continue the rebuild that was running at the last shutdown
+    pub async fn resume_interrupted(&self) -> Result<()> {
+        if let Some(rebuild) = self.db.get_running_search_index_rebuild().await? {
+            info!(
+                "Resuming search index rebuild {} at {} of {} documents",
+                rebuild.id, rebuild.processed_documents, rebuild.total_documents
+            );
+            self.spawn(rebuild);
+        }
+        Ok(())
+    }
+
+    fn spawn(&self, rebuild: SearchIndexRebuild) {
+        let service = self.clone();
+        spawn_guarded(format!("Search index rebuild {}", rebuild.id), async move {
+            service.run(rebuild).await;
+        });
+    }
+
+    async fn run(&self, rebuild: SearchIndexRebuild) {
+        let mut after = rebuild.last_document_id;
+        let result = loop {
+            match self.db.rebuild_search_index_batch(&rebuild, after, BATCH_SIZE).await {
+                Ok(Some(last_id)) => after = Some(last_id),
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        // Translations and the text of annotations, comments and custom fields are few
+        // next to documents and are redone in one go
+        let result = match result {
+            Ok(()) => self.db.rebuild_translation_search_vectors(rebuild.language.as_deref()).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        let result = match result {
+            Ok(()) => self.db.rebuild_field_search_vectors(rebuild.language.as_deref()).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+
+        let error = result.as_ref().err().map(|e| e.to_string());
+        match &error {
+            None => info!("Search index rebuild {} completed", rebuild.id),
+            Some(e) => error!("Search index rebuild {} failed: {}", rebuild.id, e),
+        }
+        if let Err(e) = self.db.finish_search_index_rebuild(rebuild.id, error.as_deref()).await {
+            error!("Failed to record the end of search index rebuild {}: {}", rebuild.id, e);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Tokens for public document links and the watermark stamped on PDFs downloaded
+//! through them. Watermarking runs Ghostscript, which the OCR tooling already installs.
+
+use anyhow::{anyhow, Result};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::services::converter_sandbox::SandboxedCommand;
+
+const TOKEN_BYTES: usize = 32;
+const DEFAULT_TIMEOUT_SECONDS: u64 = 60;
+/// Longest watermark text kept; the rest is cut off
+pub const MAX_WATERMARK_LENGTH: usize = 120;
+
+/// A new random link token, hex encoded
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// What is stored for a token, so a leaked database does not leak working links
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+pub fn can_watermark(mime_type: &str) -> bool {
+    mime_type == "application/pdf"
+}
+
+/// Draw `text` diagonally across every page of a PDF in light grey, and along the bottom
+/// edge so it survives cropping. `GHOSTSCRIPT_PATH` overrides the `gs` binary and
+/// `WATERMARK_TIMEOUT_SECONDS` bounds the run.
+pub async fn watermark_pdf(data: &[u8], text: &str) -> Result<Vec<u8>> {
+    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let work_dir = PathBuf::from(temp_dir).join(format!("watermark_{}", Uuid::new_v4()));
+    tokio::fs::create_dir_all(&work_dir).await?;
+
+    let result = watermark_in(&work_dir, data, text).await;
+    let _ = tokio::fs::remove_dir_all(&work_dir).await;
+    result
+}
+
+async fn watermark_in(work_dir: &std::path::Path, data: &[u8], text: &str) -> Result<Vec<u8>> {
+    let input = work_dir.join("input.pdf");
+    let output_path = work_dir.join("output.pdf");
+    tokio::fs::write(&input, data).await?;
+
+    let binary = std::env::var("GHOSTSCRIPT_PATH").unwrap_or_else(|_| "gs".to_string());
+    let timeout = std::env::var("WATERMARK_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECONDS);
+
+    let output = SandboxedCommand::new(&binary)
+        .args(["-q", "-dBATCH", "-dNOPAUSE", "-dSAFER", "-sDEVICE=pdfwrite"])
+        .arg(format!("-sOutputFile={}", output_path.display()))
+        .arg("-c")
+        .arg(end_page_procedure(text))
+        .arg("-f")
+        .arg(&input)
+        .current_dir(work_dir)
+        .input(data)
+        .timeout(Duration::from_secs(timeout))
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Watermarking failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    tokio::fs::read(&output_path)
+        .await
+        .map_err(|e| anyhow!("Watermarking produced no PDF: {}", e))
+}
+
+/// PostScript installing an EndPage hook that draws the watermark on each page as it
+/// is written. Reason code 2 is device deactivation, where nothing may be drawn.
+fn end_page_procedure(text: &str) -> String {
+    let text = postscript_string(text);
+    format!(
+        "<< /EndPage {{ exch pop dup 2 ne {{ pop gsave \
+         currentpagedevice /PageSize get aload pop /h exch def /w exch def \
+         0.75 setgray /Helvetica-Bold findfont w h add 40 div scalefont setfont \
+         w 2 div h 2 div translate h w atan rotate \
+         {text} dup stringwidth pop 2 div neg 0 moveto show grestore \
+         gsave 0.5 setgray /Helvetica findfont 8 scalefont setfont \
+         18 12 moveto {text} show grestore true }} {{ pop false }} ifelse }} >> setpagedevice",
+        text = text
+    )
+}
+
+/// A PostScript string literal. Non-ASCII characters become `?` since the standard
+/// fonts only cover Latin-1 and the encoding is not set up for it.
+fn postscript_string(text: &str) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('(');
+    for c in text.chars().take(MAX_WATERMARK_LENGTH) {
+        match c {
+            '(' | ')' | '\\' => {
+                literal.push('\\');
+                literal.push(c);
+            }
+            ' '..='~' => literal.push(c),
+            _ => literal.push('?'),
+        }
+    }
+    literal.push(')');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_random_and_hashed() {
+        let token = generate_token();
+        assert_eq!(token.len(), TOKEN_BYTES * 2);
+        assert_ne!(token, generate_token());
+        assert_eq!(hash_token(&token), hash_token(&token));
+        assert_ne!(hash_token(&token), token);
+        assert_eq!(hash_token(&token).len(), 64);
+    }
+
+    #[test]
+    fn test_postscript_string_escaping() {
+        assert_eq!(postscript_string("For ACME (draft)"), "(For ACME \\(draft\\))");
+        assert_eq!(postscript_string("a\\b"), "(a\\\\b)");
+        assert_eq!(postscript_string("Müller\n"), "(M?ller?)");
+        let long = "x".repeat(MAX_WATERMARK_LENGTH + 10);
+        assert_eq!(postscript_string(&long).len(), MAX_WATERMARK_LENGTH + 2);
+    }
+
+    #[test]
+    fn test_can_watermark() {
+        assert!(can_watermark("application/pdf"));
+        assert!(!can_watermark("image/png"));
+    }
+}
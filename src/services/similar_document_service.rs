@@ -0,0 +1,258 @@
+use anyhow::Result;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "ocr")]
+use tracing::debug;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{CreateNotification, Document};
+use crate::ocr::perceptual_hash::hamming_distance;
+use crate::services::file_service::FileService;
+use crate::services::i18n;
+
+/// Pages beyond this are not hashed
+const DEFAULT_MAX_PAGES: usize = 5;
+/// Resolution pages are rendered at for hashing; the hash only sees a 9x8 grid
+#[cfg(feature = "ocr")]
+const RENDER_DPI: u32 = 36;
+/// Bits per page hash that may differ for two documents to count as similar
+pub const DEFAULT_MAX_DISTANCE: u32 = 10;
+const SCAN_BATCH: i64 = 50;
+
+/// Finds likely duplicates that are not byte-identical, such as two scans of the
+/// same paper, by comparing perceptual hashes of their rendered pages
+pub struct SimilarDocumentService {
+    db: Database,
+    file_service: FileService,
+    max_pages: usize,
+}
+
+impl SimilarDocumentService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        let max_pages = std::env::var("PERCEPTUAL_HASH_MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_PAGES)
+            .max(1);
+
+        Self { db, file_service, max_pages }
+    }
+
+    /// Whether pages are hashed automatically after OCR; on unless
+    /// `PERCEPTUAL_HASH_ENABLED` is false
+    pub fn enabled_after_ocr() -> bool {
+        std::env::var("PERCEPTUAL_HASH_ENABLED")
+            .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+            .unwrap_or(true)
+    }
+
+    /// Render the document's first pages and store their hashes. Returns the number
+    /// of pages hashed.
+    #[cfg(feature = "ocr")]
+    pub async fn hash_document(&self, document: &Document) -> Result<usize> {
+        use crate::ocr::{page_images, perceptual_hash};
+
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let pages = page_images::render_pages(&data, &document.mime_type, RENDER_DPI, self.max_pages).await?;
+        let hashes: Vec<i64> = pages.iter().map(|page| perceptual_hash::page_hash(page) as i64).collect();
+
+        self.db.replace_document_page_hashes(document.id, &hashes).await?;
+        debug!("Stored {} page hashes for document {}", hashes.len(), document.id);
+        Ok(hashes.len())
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub async fn hash_document(&self, _document: &Document) -> Result<usize> {
+        anyhow::bail!("Perceptual hashing requires OCR feature")
+    }
+
+    /// Hash a freshly processed document and notify its owner when it looks like a
+    /// re-scan of a document they already have
+    pub async fn hash_and_flag(&self, document: &Document) -> Result<()> {
+        self.hash_document(document).await?;
+
+        let signatures = self.db.get_user_page_hashes(document.user_id).await?;
+        let dismissed = self.db.get_similar_document_dismissals(document.user_id).await?;
+        let similar = similar_to(document.id, &signatures, &dismissed, DEFAULT_MAX_DISTANCE);
+        if similar.is_empty() {
+            return Ok(());
+        }
+
+        info!("Document {} looks like a re-scan of {} other documents", document.id, similar.len());
+        let locale = i18n::locale_of(&self.db, document.user_id).await;
+        let notification = CreateNotification {
+            action_url: Some(format!("/documents/{}", document.id)),
+            metadata: Some(json!({
+                "document_id": document.id,
+                "similar_document_ids": similar.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            })),
+            ..i18n::notification(
+                &locale,
+                "info",
+                "notification.possible_duplicate",
+                &[("filename", document.original_filename.clone()), ("count", similar.len().to_string())],
+            )
+        };
+        self.db.create_notification(document.user_id, &notification).await?;
+        Ok(())
+    }
+
+    /// Hash every PDF and image of the user that has no page hashes yet. Returns the
+    /// number of documents hashed.
+    pub async fn hash_unhashed(&self, user_id: Uuid) -> Result<usize> {
+        let mut hashed = 0;
+        let mut after = None;
+        loop {
+            let documents = self.db.get_unhashed_documents(user_id, after, SCAN_BATCH).await?;
+            let Some(last) = documents.last() else {
+                break;
+            };
+            after = Some(last.id);
+
+            for document in &documents {
+                match self.hash_document(document).await {
+                    Ok(_) => hashed += 1,
+                    Err(e) => warn!("Failed to hash pages of document {}: {}", document.id, e),
+                }
+            }
+        }
+        Ok(hashed)
+    }
+
+    /// Groups of the user's documents that look alike, each starting with its oldest
+    /// document, with every member's distance to it
+    pub async fn find_groups(&self, user_id: Uuid, max_distance: u32) -> Result<Vec<Vec<(Uuid, u32)>>> {
+        let signatures = self.db.get_user_page_hashes(user_id).await?;
+        let dismissed = self.db.get_similar_document_dismissals(user_id).await?;
+        Ok(group_similar(&signatures, &dismissed, max_distance))
+    }
+}
+
+/// Largest page hash distance between two documents; None when their page counts differ
+pub fn signature_distance(a: &[i64], b: &[i64]) -> Option<u32> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    a.iter().zip(b).map(|(x, y)| hamming_distance(*x as u64, *y as u64)).max()
+}
+
+fn is_dismissed(dismissed: &HashSet<(Uuid, Uuid)>, a: Uuid, b: Uuid) -> bool {
+    dismissed.contains(&if a < b { (a, b) } else { (b, a) })
+}
+
+/// Documents within `max_distance` of `document_id`, closest first
+pub fn similar_to(
+    document_id: Uuid,
+    signatures: &[(Uuid, Vec<i64>)],
+    dismissed: &HashSet<(Uuid, Uuid)>,
+    max_distance: u32,
+) -> Vec<(Uuid, u32)> {
+    let Some((_, target)) = signatures.iter().find(|(id, _)| *id == document_id) else {
+        return Vec::new();
+    };
+
+    let mut similar: Vec<(Uuid, u32)> = signatures
+        .iter()
+        .filter(|(id, _)| *id != document_id && !is_dismissed(dismissed, document_id, *id))
+        .filter_map(|(id, hashes)| {
+            signature_distance(target, hashes)
+                .filter(|distance| *distance <= max_distance)
+                .map(|distance| (*id, distance))
+        })
+        .collect();
+    similar.sort_by_key(|(_, distance)| *distance);
+    similar
+}
+
+/// Connected groups of similar documents. `signatures` should be oldest first; each
+/// group keeps that order and lists distances to its first document.
+pub fn group_similar(
+    signatures: &[(Uuid, Vec<i64>)],
+    dismissed: &HashSet<(Uuid, Uuid)>,
+    max_distance: u32,
+) -> Vec<Vec<(Uuid, u32)>> {
+    fn root(parent: &mut [usize], mut index: usize) -> usize {
+        while parent[index] != index {
+            parent[index] = parent[parent[index]];
+            index = parent[index];
+        }
+        index
+    }
+
+    // Only documents with the same number of hashed pages can match
+    let mut by_page_count: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, (_, hashes)) in signatures.iter().enumerate() {
+        if !hashes.is_empty() {
+            by_page_count.entry(hashes.len()).or_default().push(index);
+        }
+    }
+
+    let mut parent: Vec<usize> = (0..signatures.len()).collect();
+    for members in by_page_count.values() {
+        for (position, &a) in members.iter().enumerate() {
+            for &b in &members[position + 1..] {
+                let (id_a, hashes_a) = &signatures[a];
+                let (id_b, hashes_b) = &signatures[b];
+                let close = signature_distance(hashes_a, hashes_b).is_some_and(|distance| distance <= max_distance);
+                if close && !is_dismissed(dismissed, *id_a, *id_b) {
+                    let (root_a, root_b) = (root(&mut parent, a), root(&mut parent, b));
+                    parent[root_a.max(root_b)] = root_a.min(root_b);
+                }
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+    for index in 0..signatures.len() {
+        let group_root = root(&mut parent, index);
+        match group_of_root.get(&group_root) {
+            Some(&group) => groups[group].push(index),
+            None => {
+                group_of_root.insert(group_root, groups.len());
+                groups.push(vec![index]);
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let first = &signatures[members[0]].1;
+            members
+                .iter()
+                .map(|&index| {
+                    let (id, hashes) = &signatures[index];
+                    (*id, signature_distance(first, hashes).unwrap_or(0))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_similar_documents() {
+        let ids: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        let signatures = vec![
+            (ids[0], vec![0b1111_0000, 0b1010]),
+            (ids[1], vec![0b1111_0001, 0b1010]),
+            // Same first page, but a different page count
+            (ids[2], vec![0b1111_0000]),
+            (ids[3], vec![-1, -1]),
+            (ids[4], vec![0b1111_0011, 0b1011]),
+        ];
+
+        let groups = group_similar(&signatures, &HashSet::new(), 3);
+        assert_eq!(groups, vec![vec![(ids[0], 0), (ids[1], 1), (ids[4], 2)]]);
+
+        let dismissed: HashSet<(Uuid, Uuid)> = [(ids[0].min(ids[1]), ids[0].max(ids[1]))].into_iter().collect();
+        assert_eq!(similar_to(ids[0], &signatures, &dismissed, 3), vec![(ids[4], 2)]);
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+use crate::models::{
+    DropboxSourceConfig, ImapSourceConfig, LocalFolderSourceConfig, OneDriveSourceConfig, RemoteProcessedAction,
+    S3SourceConfig, SftpSourceConfig, SourceRuleDecision, SourceRuleSimulation, SourceType, WebDAVSourceConfig,
+};
+use crate::services::{cloud_drive, imap_service, sftp_service};
+
+/// The file or message a source's rules are evaluated against
+#[derive(Debug, Clone, Default)]
+pub struct RuleSample {
+    /// Path as the source reports it; for IMAP, the attachment's filename is its last segment
+    pub path: String,
+    pub from: Option<String>,
+    pub subject: Option<String>,
+}
+
+/// Evaluate a source configuration's filter and label rules against a sample the way
+/// a sync would, without touching the source or storing anything
+pub fn simulate(source_type: SourceType, config: &serde_json::Value, sample: &RuleSample) -> Result<SourceRuleSimulation> {
+    let mut simulation = SourceRuleSimulation {
+        source_type,
+        path: sample.path.clone(),
+        decisions: Vec::new(),
+        would_ingest: true,
+        labels: Vec::new(),
+    };
+
+    match source_type {
+        SourceType::WebDAV => {
+            let config: WebDAVSourceConfig = parse_config(config, "WebDAV")?;
+            watch_folders(&mut simulation, &config.watch_folders, &sample.path, true);
+            listed_extension(&mut simulation, &config.file_extensions, &sample.path);
+        }
+        SourceType::LocalFolder => {
+            let config: LocalFolderSourceConfig = parse_config(config, "Local Folder")?;
+            watch_folders(&mut simulation, &config.watch_folders, &sample.path, config.recursive);
+            listed_extension(&mut simulation, &config.file_extensions, &sample.path);
+        }
+        SourceType::S3 => {
+            let config: S3SourceConfig = parse_config(config, "S3")?;
+            let key = sample.path.trim_start_matches('/');
+            let prefix = config
+                .watch_folders
+                .iter()
+                .find(|prefix| key.starts_with(prefix.trim_start_matches('/')));
+            gate(
+                &mut simulation,
+                "watch_folders",
+                prefix.is_some(),
+                match prefix {
+                    Some(prefix) => format!("key is under prefix '{}'", prefix),
+                    None => format!("key is under none of {:?}", config.watch_folders),
+                },
+            );
+            listed_extension(&mut simulation, &config.file_extensions, &sample.path);
+        }
+        SourceType::Imap => simulate_imap(&mut simulation, parse_config(config, "IMAP")?, sample),
+        SourceType::Dropbox => {
+            let config: DropboxSourceConfig = parse_config(config, "Dropbox")?;
+            cloud_drive_rules(&mut simulation, &config.root_path, &config.include_patterns, &config.exclude_patterns, &sample.path)?;
+        }
+        SourceType::OneDrive => {
+            let config: OneDriveSourceConfig = parse_config(config, "OneDrive")?;
+            cloud_drive_rules(&mut simulation, &config.root_path, &config.include_patterns, &config.exclude_patterns, &sample.path)?;
+        }
+        SourceType::Sftp => {
+            let config: SftpSourceConfig = parse_config(config, "SFTP")?;
+            let in_remote_path = under_folder(&sample.path, &config.remote_path, config.recursive);
+            gate(
+                &mut simulation,
+                "remote_path",
+                in_remote_path,
+                format!(
+                    "'{}'{} {}",
+                    config.remote_path,
+                    if config.recursive { " or a subdirectory" } else { "" },
+                    if in_remote_path { "contains the file" } else { "does not contain the file" }
+                ),
+            );
+            if config.processed_action == RemoteProcessedAction::Move {
+                if let Some(processed_path) = config.processed_path.as_deref() {
+                    let processed = under_folder(&sample.path, processed_path, true);
+                    gate(
+                        &mut simulation,
+                        "processed_path",
+                        !processed,
+                        if processed { "file is already in the processed directory" } else { "file is not in the processed directory" },
+                    );
+                }
+            }
+            if !config.file_extensions.is_empty() {
+                let selected = sftp_service::extension_selected(&sample.path, &config.file_extensions);
+                gate(&mut simulation, "file_extensions", selected, extension_detail(&sample.path, &config.file_extensions, selected));
+            }
+        }
+    }
+
+    Ok(simulation)
+}
+
+fn simulate_imap(simulation: &mut SourceRuleSimulation, config: ImapSourceConfig, sample: &RuleSample) {
+    let from = sample.from.as_deref().unwrap_or("");
+    let subject = sample.subject.as_deref().unwrap_or("");
+
+    let senders: Vec<&str> = config.from_filters.iter().map(|f| f.trim()).filter(|f| !f.is_empty()).collect();
+    if !senders.is_empty() {
+        let sender = senders.iter().find(|sender| contains_ignore_case(from, sender));
+        gate(
+            simulation,
+            "from_filters",
+            sender.is_some(),
+            match sender {
+                Some(sender) => format!("sender '{}' contains '{}'", from, sender),
+                None => format!("sender '{}' contains none of {:?}", from, senders),
+            },
+        );
+    }
+
+    if let Some(filter) = config.subject_filter.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        let matched = contains_ignore_case(subject, filter);
+        gate(
+            simulation,
+            "subject_filter",
+            matched,
+            format!("subject '{}' {} '{}'", subject, if matched { "contains" } else { "does not contain" }, filter),
+        );
+    }
+
+    if !config.file_extensions.is_empty() {
+        let extension = extension_of(&sample.path);
+        let selected = config.file_extensions.iter().any(|e| e.eq_ignore_ascii_case(&extension));
+        gate(simulation, "file_extensions", selected, extension_detail(&sample.path, &config.file_extensions, selected));
+    }
+
+    for (index, rule) in config.label_rules.iter().enumerate() {
+        let labels = imap_service::matching_labels(std::slice::from_ref(rule), Some(from), Some(subject));
+        simulation.decisions.push(SourceRuleDecision {
+            rule: format!("label_rules[{}]", index),
+            matched: !labels.is_empty(),
+            detail: format!("label '{}'", rule.label.trim()),
+        });
+    }
+    simulation.labels = imap_service::matching_labels(&config.label_rules, Some(from), Some(subject));
+}
+
+fn cloud_drive_rules(
+    simulation: &mut SourceRuleSimulation,
+    root_path: &str,
+    include_patterns: &[String],
+    exclude_patterns: &[String],
+    path: &str,
+) -> Result<()> {
+    let relative_path = cloud_drive::relative_to_root(path, root_path);
+    gate(
+        simulation,
+        "root_path",
+        relative_path.is_some(),
+        match &relative_path {
+            Some(relative_path) => format!("path below the root is '{}'", relative_path),
+            None => format!("path is outside '{}'", root_path),
+        },
+    );
+    let relative_path = relative_path.unwrap_or_default();
+
+    let mut included = include_patterns.is_empty();
+    for (index, pattern) in include_patterns.iter().enumerate() {
+        let matched = cloud_drive::PathFilter::new(std::slice::from_ref(pattern), &[])?.matches(&relative_path);
+        included |= matched;
+        simulation.decisions.push(SourceRuleDecision {
+            rule: format!("include_patterns[{}]", index),
+            matched,
+            detail: format!("include '{}'", pattern),
+        });
+    }
+    if !included {
+        simulation.would_ingest = false;
+    }
+
+    for (index, pattern) in exclude_patterns.iter().enumerate() {
+        let matched = !cloud_drive::PathFilter::new(&[], std::slice::from_ref(pattern))?.matches(&relative_path);
+        if matched {
+            simulation.would_ingest = false;
+        }
+        simulation.decisions.push(SourceRuleDecision {
+            rule: format!("exclude_patterns[{}]", index),
+            matched,
+            detail: format!("exclude '{}'", pattern),
+        });
+    }
+
+    Ok(())
+}
+
+/// WebDAV and local folder sources only sync files below one of their watch folders
+fn watch_folders(simulation: &mut SourceRuleSimulation, folders: &[String], path: &str, recursive: bool) {
+    let folder = folders.iter().find(|folder| under_folder(path, folder, recursive));
+    gate(
+        simulation,
+        "watch_folders",
+        folder.is_some(),
+        match folder {
+            Some(folder) => format!("file is in watch folder '{}'", folder),
+            None => format!("file is in none of {:?}", folders),
+        },
+    );
+}
+
+/// WebDAV, local folder and S3 sources only sync the listed extensions
+fn listed_extension(simulation: &mut SourceRuleSimulation, extensions: &[String], path: &str) {
+    let selected = extensions.contains(&extension_of(path));
+    gate(simulation, "file_extensions", selected, extension_detail(path, extensions, selected));
+}
+
+/// Record a rule the sample must pass to be ingested
+fn gate(simulation: &mut SourceRuleSimulation, rule: &str, matched: bool, detail: impl Into<String>) {
+    if !matched {
+        simulation.would_ingest = false;
+    }
+    simulation.decisions.push(SourceRuleDecision {
+        rule: rule.to_string(),
+        matched,
+        detail: detail.into(),
+    });
+}
+
+fn parse_config<T: DeserializeOwned>(config: &serde_json::Value, source_kind: &str) -> Result<T> {
+    serde_json::from_value(config.clone()).map_err(|e| anyhow!("Invalid {} configuration: {}", source_kind, e))
+}
+
+fn under_folder(path: &str, folder: &str, recursive: bool) -> bool {
+    let path = path.trim_matches('/');
+    let folder = folder.trim().trim_matches('/');
+    let below = if folder.is_empty() {
+        Some(path)
+    } else {
+        path.strip_prefix(folder).and_then(|rest| rest.strip_prefix('/'))
+    };
+    below.is_some_and(|below| !below.is_empty() && (recursive || !below.contains('/')))
+}
+
+fn extension_of(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+fn extension_detail(path: &str, extensions: &[String], selected: bool) -> String {
+    format!(
+        "extension '{}' is {}listed in {:?}",
+        extension_of(path),
+        if selected { "" } else { "not " },
+        extensions
+    )
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample(path: &str) -> RuleSample {
+        RuleSample { path: path.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_cloud_drive_patterns() {
+        let config = json!({
+            "app_key": "key", "app_secret": "secret", "refresh_token": "token",
+            "root_path": "/Scans",
+            "include_patterns": ["invoices/**/*.pdf", "*.png"],
+            "exclude_patterns": ["**/drafts/**"],
+            "auto_sync": true, "sync_interval_minutes": 30
+        });
+
+        let simulation = simulate(SourceType::Dropbox, &config, &sample("/Scans/invoices/2024/march.pdf")).unwrap();
+        assert!(simulation.would_ingest);
+        let matched: Vec<&str> = simulation.decisions.iter().filter(|d| d.matched).map(|d| d.rule.as_str()).collect();
+        assert_eq!(matched, vec!["root_path", "include_patterns[0]"]);
+
+        let simulation = simulate(SourceType::Dropbox, &config, &sample("/Scans/invoices/drafts/may.pdf")).unwrap();
+        assert!(!simulation.would_ingest);
+        assert!(simulation.decisions.iter().any(|d| d.rule == "exclude_patterns[0]" && d.matched));
+
+        let simulation = simulate(SourceType::Dropbox, &config, &sample("/Other/a.png")).unwrap();
+        assert!(!simulation.would_ingest);
+    }
+
+    #[test]
+    fn test_imap_filters_and_labels() {
+        let config = json!({
+            "server": "imap.example.com", "username": "scans", "password": "secret",
+            "from_filters": ["@acme.com"],
+            "subject_filter": "invoice",
+            "file_extensions": ["pdf"],
+            "label_rules": [
+                { "from_contains": "@acme.com", "label": "ACME" },
+                { "subject_contains": "contract", "label": "Contracts" }
+            ],
+            "auto_sync": true, "sync_interval_minutes": 5
+        });
+        let mut sample = sample("INBOX/42/invoice.PDF");
+        sample.from = Some("Billing <billing@ACME.com>".to_string());
+        sample.subject = Some("Your Invoice 42".to_string());
+
+        let simulation = simulate(SourceType::Imap, &config, &sample).unwrap();
+        assert!(simulation.would_ingest);
+        assert_eq!(simulation.labels, vec!["ACME".to_string()]);
+        assert!(simulation.decisions.iter().any(|d| d.rule == "label_rules[1]" && !d.matched));
+
+        sample.subject = Some("Hello".to_string());
+        assert!(!simulate(SourceType::Imap, &config, &sample).unwrap().would_ingest);
+    }
+
+    #[test]
+    fn test_watch_folders_and_extensions() {
+        let config = json!({
+            "watch_folders": ["/data/inbox"], "file_extensions": ["pdf"],
+            "auto_sync": true, "sync_interval_minutes": 5, "recursive": false, "follow_symlinks": false
+        });
+        assert!(simulate(SourceType::LocalFolder, &config, &sample("/data/inbox/a.pdf")).unwrap().would_ingest);
+        assert!(!simulate(SourceType::LocalFolder, &config, &sample("/data/inbox/sub/a.pdf")).unwrap().would_ingest);
+        assert!(!simulate(SourceType::LocalFolder, &config, &sample("/data/inbox/a.jpg")).unwrap().would_ingest);
+        assert!(simulate(SourceType::LocalFolder, &json!({}), &sample("/a.pdf")).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::{
+    db::Database,
+    models::{
+        FileIngestionInfo, LocalFolderSourceConfig, RemoteProcessedAction, S3SourceConfig, SftpSourceConfig, Source,
+        SourceSyncPreview, SourceType, SyncPreviewAction, SyncPreviewEntry, WebDAVSourceConfig, WebDAVSyncDirection,
+    },
+    services::local_folder_service::LocalFolderService,
+    services::s3_service::S3Service,
+    services::sftp_service::SftpService,
+    services::webdav::{write_back, WebDAVConfig, WebDAVService},
+};
+
+/// Source types whose files can be listed without side effects. IMAP, Dropbox and
+/// OneDrive only report changes through state the sync itself advances.
+pub fn supports(source_type: SourceType) -> bool {
+    matches!(source_type, SourceType::WebDAV | SourceType::LocalFolder | SourceType::S3 | SourceType::Sftp)
+}
+
+/// Walk the source and report what a sync would do, keeping at most `limit` entries.
+/// Files are matched against earlier ingestions by path and ETag; content that was
+/// already ingested from a different path is only recognised once downloaded.
+pub async fn preview(db: &Database, source: &Source, limit: usize) -> Result<SourceSyncPreview> {
+    let mut preview = Preview::new(source, limit, db.get_source_document_paths(source.id).await?);
+
+    match source.source_type {
+        SourceType::WebDAV => preview_webdav(db, source, &mut preview).await?,
+        SourceType::LocalFolder => preview_local_folder(source, &mut preview).await?,
+        SourceType::S3 => preview_s3(db, source, &mut preview).await?,
+        SourceType::Sftp => preview_sftp(db, source, &mut preview).await?,
+        other => return Err(anyhow!("Sync preview is not supported for {} sources", other)),
+    }
+
+    Ok(preview.finish())
+}
+
+async fn preview_webdav(db: &Database, source: &Source, preview: &mut Preview) -> Result<()> {
+    let config: WebDAVSourceConfig = serde_json::from_value(source.config.clone())
+        .map_err(|e| anyhow!("Invalid WebDAV config: {}", e))?;
+    let webdav_config = WebDAVConfig {
+        server_url: config.server_url.clone(),
+        username: config.username.clone(),
+        password: config.password.clone(),
+        watch_folders: config.watch_folders.clone(),
+        file_extensions: config.file_extensions.clone(),
+        timeout_seconds: 180,
+        server_type: config.server_type.clone(),
+    };
+    let service = WebDAVService::new(webdav_config)?;
+
+    // A full listing; smart sync would record directory ETags as it goes
+    for folder in &config.watch_folders {
+        match service.discover_files(folder, true).await {
+            Ok(files) => preview.add_files(files, &config.file_extensions, &HashMap::new()),
+            Err(e) => preview.warn(format!("Failed to list {}: {}", folder, e)),
+        }
+    }
+
+    if config.sync_direction == WebDAVSyncDirection::Bidirectional {
+        for change in db.get_pending_webdav_outgoing_changes(source.id).await? {
+            if change.operation == write_back::OPERATION_DELETE {
+                preview.push(SyncPreviewEntry {
+                    action: SyncPreviewAction::Delete,
+                    path: change.remote_path,
+                    size: None,
+                    reason: "deleted in Readur; will be deleted on the server".to_string(),
+                    existing_document_id: Some(change.document_id),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn preview_local_folder(source: &Source, preview: &mut Preview) -> Result<()> {
+    let config: LocalFolderSourceConfig = serde_json::from_value(source.config.clone())
+        .map_err(|e| anyhow!("Invalid LocalFolder config: {}", e))?;
+    let service = LocalFolderService::new(config.clone())?;
+
+    for folder in &config.watch_folders {
+        match service.discover_files_in_folder(folder).await {
+            Ok(files) => preview.add_files(files, &config.file_extensions, &HashMap::new()),
+            Err(e) => preview.warn(format!("Failed to list {}: {}", folder, e)),
+        }
+    }
+
+    Ok(())
+}
+
+async fn preview_s3(db: &Database, source: &Source, preview: &mut Preview) -> Result<()> {
+    let config: S3SourceConfig = serde_json::from_value(source.config.clone())
+        .map_err(|e| anyhow!("Invalid S3 config: {}", e))?;
+    let service = S3Service::new(config.clone()).await?;
+    let seen_etags = db.get_s3_object_etags(source.id).await?;
+
+    for folder in &config.watch_folders {
+        match service.discover_files_in_folder(folder).await {
+            Ok(files) => preview.add_files(files, &config.file_extensions, &seen_etags),
+            Err(e) => preview.warn(format!("Failed to list {}: {}", folder, e)),
+        }
+    }
+
+    Ok(())
+}
+
+async fn preview_sftp(db: &Database, source: &Source, preview: &mut Preview) -> Result<()> {
+    let config: SftpSourceConfig = serde_json::from_value(source.config.clone())
+        .map_err(|e| anyhow!("Invalid SFTP config: {}", e))?;
+    let connection = SftpService::new(config.clone())?.connect().await?;
+    let files = connection.list_files().await;
+    connection.close().await;
+    let files = files?;
+
+    let known_files = if config.processed_action == RemoteProcessedAction::Keep {
+        db.get_remote_source_files(source.id).await?
+    } else {
+        HashMap::new()
+    };
+
+    for file in files {
+        preview.result.files_discovered += 1;
+        if known_files.get(&file.path) == Some(&(file.size as i64, file.modified_at)) {
+            preview.push(SyncPreviewEntry {
+                action: SyncPreviewAction::Skip,
+                path: file.path,
+                size: Some(file.size as i64),
+                reason: "unchanged since the last sync".to_string(),
+                existing_document_id: None,
+            });
+            continue;
+        }
+
+        let after_ingestion = match config.processed_action {
+            RemoteProcessedAction::Keep => None,
+            RemoteProcessedAction::Delete => Some("deleted from the server once ingested".to_string()),
+            RemoteProcessedAction::Move => Some(format!(
+                "moved to {} once ingested",
+                config.processed_path.as_deref().unwrap_or_default()
+            )),
+        };
+        let entry = preview.classify(file.path, file.size as i64, None, None);
+        let path = entry.path.clone();
+        preview.push(entry);
+        if let Some(reason) = after_ingestion {
+            preview.push(SyncPreviewEntry {
+                action: SyncPreviewAction::Delete,
+                path,
+                size: Some(file.size as i64),
+                reason,
+                existing_document_id: None,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+struct Preview {
+    result: SourceSyncPreview,
+    limit: usize,
+    known_documents: HashMap<String, (Uuid, Option<String>)>,
+    seen_paths: HashSet<String>,
+}
+
+impl Preview {
+    fn new(source: &Source, limit: usize, known_documents: HashMap<String, (Uuid, Option<String>)>) -> Self {
+        Self {
+            result: SourceSyncPreview {
+                source_id: source.id,
+                source_type: source.source_type,
+                files_discovered: 0,
+                would_ingest: 0,
+                would_ingest_bytes: 0,
+                would_skip: 0,
+                would_delete: 0,
+                entries: Vec::new(),
+                truncated: false,
+                warnings: Vec::new(),
+            },
+            limit,
+            known_documents,
+            seen_paths: HashSet::new(),
+        }
+    }
+
+    /// Files from a folder listing, filtered the way the sync filters them. `seen_etags`
+    /// holds ETags recorded by the sync itself, which take precedence over the documents'.
+    fn add_files(&mut self, files: Vec<FileIngestionInfo>, file_extensions: &[String], seen_etags: &HashMap<String, String>) {
+        for file in files {
+            if file.is_directory || !has_selected_extension(&file.name, file_extensions) {
+                continue;
+            }
+            // Watch folders may overlap
+            if !self.seen_paths.insert(file.relative_path.clone()) {
+                continue;
+            }
+
+            self.result.files_discovered += 1;
+            let seen_etag = seen_etags.get(&file.relative_path).map(String::as_str);
+            let etag = Some(file.etag.as_str()).filter(|etag| !etag.is_empty());
+            let entry = self.classify(file.relative_path.clone(), file.size, etag, seen_etag);
+            self.push(entry);
+        }
+    }
+
+    fn classify(&self, path: String, size: i64, etag: Option<&str>, seen_etag: Option<&str>) -> SyncPreviewEntry {
+        let known = self.known_documents.get(&path);
+        let existing_document_id = known.map(|(id, _)| *id);
+        let recorded_etag = seen_etag.or_else(|| known.and_then(|(_, etag)| etag.as_deref()));
+
+        let (action, reason) = match (known, recorded_etag, etag) {
+            (_, Some(recorded), Some(current)) if recorded == current => {
+                (SyncPreviewAction::Skip, "unchanged since the last sync")
+            }
+            (Some(_), Some(_), Some(_)) => (SyncPreviewAction::Ingest, "changed since it was ingested"),
+            (Some(_), _, _) => (SyncPreviewAction::Skip, "already ingested"),
+            (None, _, _) => (SyncPreviewAction::Ingest, "new"),
+        };
+
+        SyncPreviewEntry {
+            action,
+            path,
+            size: Some(size),
+            reason: reason.to_string(),
+            existing_document_id,
+        }
+    }
+
+    fn push(&mut self, entry: SyncPreviewEntry) {
+        match entry.action {
+            SyncPreviewAction::Ingest => {
+                self.result.would_ingest += 1;
+                self.result.would_ingest_bytes += entry.size.unwrap_or(0);
+            }
+            SyncPreviewAction::Skip => self.result.would_skip += 1,
+            SyncPreviewAction::Delete => self.result.would_delete += 1,
+        }
+
+        if self.result.entries.len() < self.limit {
+            self.result.entries.push(entry);
+        } else {
+            self.result.truncated = true;
+        }
+    }
+
+    fn warn(&mut self, warning: String) {
+        self.result.warnings.push(warning);
+    }
+
+    fn finish(self) -> SourceSyncPreview {
+        self.result
+    }
+}
+
+fn has_selected_extension(name: &str, file_extensions: &[String]) -> bool {
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    file_extensions.contains(&extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::models::SourceStatus;
+
+    fn source() -> Source {
+        Source {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: "Nextcloud".to_string(),
+            source_type: SourceType::WebDAV,
+            enabled: true,
+            config: serde_json::json!({}),
+            status: SourceStatus::Idle,
+            last_sync_at: None,
+            last_error: None,
+            last_error_at: None,
+            total_files_synced: 0,
+            total_files_pending: 0,
+            total_size_bytes: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            validation_status: None,
+            last_validation_at: None,
+            validation_score: None,
+            validation_issues: None,
+        }
+    }
+
+    #[test]
+    fn test_classify_by_path_and_etag() {
+        let ingested = Uuid::new_v4();
+        let changed = Uuid::new_v4();
+        let known = HashMap::from([
+            ("/a.pdf".to_string(), (ingested, None)),
+            ("/b.pdf".to_string(), (changed, Some("\"v1\"".to_string()))),
+        ]);
+        let mut preview = Preview::new(&source(), 1, known);
+
+        let entry = preview.classify("/a.pdf".to_string(), 10, Some("\"x\""), None);
+        assert_eq!(entry.action, SyncPreviewAction::Skip);
+        assert_eq!(entry.existing_document_id, Some(ingested));
+        assert_eq!(preview.classify("/b.pdf".to_string(), 10, Some("\"v2\""), None).action, SyncPreviewAction::Ingest);
+        assert_eq!(preview.classify("/b.pdf".to_string(), 10, Some("\"v1\""), None).action, SyncPreviewAction::Skip);
+        assert_eq!(preview.classify("/c.pdf".to_string(), 10, Some("\"e\""), Some("\"e\"")).action, SyncPreviewAction::Skip);
+        let new = preview.classify("/c.pdf".to_string(), 10, None, None);
+        assert_eq!(new.action, SyncPreviewAction::Ingest);
+
+        preview.push(new.clone());
+        preview.push(new);
+        let result = preview.finish();
+        assert_eq!(result.would_ingest, 2);
+        assert_eq!(result.would_ingest_bytes, 20);
+        assert_eq!(result.entries.len(), 1);
+        assert!(result.truncated);
+    }
+}
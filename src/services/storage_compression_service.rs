@@ -0,0 +1,419 @@
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::storage_compression::CompressedContents;
+use crate::db::Database;
+use crate::models::{CompressedOriginal, CompressionFormat, Document, StorageCompressionPolicy};
+use crate::services::converter_sandbox::SandboxedCommand;
+use crate::services::file_service::FileService;
+use crate::services::thumbnail_service::requeue_thumbnail;
+use crate::storage;
+
+/// Pause between passes over the policies
+const INTERVAL: Duration = Duration::from_secs(3600);
+/// Documents recompressed, and originals deleted, per pass
+const BATCH_SIZE: i64 = 50;
+const DEFAULT_TIMEOUT_SECONDS: u64 = 600;
+/// Long edge of an A4 page in inches; scans carry no reliable resolution, so their
+/// long edge is assumed to be a page's
+#[cfg_attr(not(feature = "ocr"), allow(dead_code))]
+const PAGE_LONG_EDGE_INCHES: f64 = 11.69;
+
+/// Recompresses stored scans matching a storage compression policy once their OCR is
+/// done, e.g. 600 dpi color TIFFs into PDFs of JPEG pages at 200 dpi, and deletes the
+/// originals kept alongside once the policy's window ends
+pub struct StorageCompressionService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl StorageCompressionService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    pub fn enabled() -> bool {
+        std::env::var("STORAGE_COMPRESSION_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false)
+    }
+
+    pub async fn run(self) {
+        if !Self::enabled() {
+            return;
+        }
+        let mut interval = tokio::time::interval(INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.purge_expired_originals().await {
+                error!("Failed to delete expired originals: {}", e);
+            }
+            if let Err(e) = self.compress_pending().await {
+                error!("Failed to recompress stored documents: {}", e);
+            }
+        }
+    }
+
+    /// Recompress one batch of documents a policy applies to; returns how many were
+    /// replaced
+    pub async fn compress_pending(&self) -> Result<usize> {
+        let policies = self.db.get_storage_compression_policies().await?;
+        let candidates = self.db.get_storage_compression_candidates(BATCH_SIZE).await?;
+        let mut compressed = 0;
+        for document in candidates {
+            let Some(policy) = policies.iter().find(|p| p.applies_to(&document.mime_type, document.file_size)) else {
+                continue;
+            };
+            match self.compress(&document, policy).await {
+                Ok(true) => compressed += 1,
+                Ok(false) => {}
+                Err(e) => warn!("Failed to recompress document {}: {}", document.id, e),
+            }
+        }
+        if compressed > 0 {
+            info!("Recompressed {} stored document(s)", compressed);
+        }
+        Ok(compressed)
+    }
+
+    /// Replace the document's file with a recompressed one when that is smaller,
+    /// keeping the original. False when it was left as it is.
+    async fn compress(&self, document: &Document, policy: &StorageCompressionPolicy) -> Result<bool> {
+        let data = self.file_service.read_file(&document.file_path).await?;
+        let compressed = match self.recompress(document, &data, policy).await? {
+            Some(compressed) if compressed.len() < data.len() => compressed,
+            compressed => {
+                // Recorded so it is not tried again every pass
+                if compressed.is_some() {
+                    info!("Recompressing document {} would not make it smaller; keeping it", document.id);
+                }
+                self.db.skip_storage_compression(document, policy).await?;
+                return Ok(false);
+            }
+        };
+
+        // The original is kept like a document version: shared content is handed over,
+        // anything else gets a copy under an id of its own
+        let original_id = Uuid::new_v4();
+        let is_blob = storage::is_blob_path(&document.file_path);
+        let original_path = if is_blob {
+            document.file_path.clone()
+        } else {
+            self.file_service
+                .save_document_file(document.user_id, original_id, &document.filename, &data)
+                .await?
+        };
+
+        let extension = policy.target_format.extension();
+        let filename = Path::new(&document.filename).with_extension(extension).to_string_lossy().into_owned();
+        let original_filename =
+            Path::new(&document.original_filename).with_extension(extension).to_string_lossy().into_owned();
+        let file_hash = format!("{:x}", Sha256::digest(&compressed));
+        let file_path = self
+            .file_service
+            .store_document_content(document.user_id, document.id, &filename, &compressed, &file_hash)
+            .await?;
+        let contents = CompressedContents {
+            filename: &filename,
+            original_filename: &original_filename,
+            file_path: &file_path,
+            file_size: compressed.len() as i64,
+            file_hash: &file_hash,
+            mime_type: policy.target_format.mime_type(),
+        };
+
+        match self.db.store_compressed_document(document, policy, original_id, &original_path, &contents).await {
+            Ok(_) => {
+                info!(
+                    "Recompressed document {} from {} to {} bytes",
+                    document.id,
+                    document.file_size,
+                    compressed.len()
+                );
+                self.file_service.invalidate_thumbnail(&document.file_path).await;
+                self.file_service.invalidate_thumbnail(&file_path).await;
+                // The original has its own copy now
+                if !is_blob && document.file_path != file_path {
+                    if let Err(e) = self.file_service.delete_stored_file(&document.file_path).await {
+                        warn!("Failed to delete previous file {}: {}", document.file_path, e);
+                    }
+                }
+                requeue_thumbnail(&self.db, document.id).await;
+                Ok(true)
+            }
+            Err(e) => {
+                if let Err(release_err) = self.file_service.release_blob(&file_path).await {
+                    warn!("Failed to release blob {}: {}", file_path, release_err);
+                }
+                if !is_blob {
+                    if file_path == document.file_path {
+                        // Put the original back so the file matches the unchanged row
+                        if let Err(restore_err) = self
+                            .file_service
+                            .save_document_file(document.user_id, document.id, &document.filename, &data)
+                            .await
+                        {
+                            warn!("Failed to restore file of document {}: {}", document.id, restore_err);
+                        }
+                    } else if !storage::is_blob_path(&file_path) {
+                        if let Err(delete_err) = self.file_service.delete_stored_file(&file_path).await {
+                            warn!("Failed to delete unused file {}: {}", file_path, delete_err);
+                        }
+                    }
+                    if let Err(delete_err) = self
+                        .file_service
+                        .delete_version_file(document.user_id, original_id, &document.filename, &original_path)
+                        .await
+                    {
+                        warn!("Failed to delete unused copy {}: {}", original_path, delete_err);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// The recompressed file, or None when the document's type cannot be recompressed
+    /// into the policy's format here
+    async fn recompress(&self, document: &Document, data: &[u8], policy: &StorageCompressionPolicy) -> Result<Option<Vec<u8>>> {
+        let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+        let work_dir = PathBuf::from(temp_dir).join(format!("compression_{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&work_dir).await?;
+
+        let result = if document.mime_type == "application/pdf" {
+            if policy.target_format == CompressionFormat::Pdf {
+                downsample_pdf(&work_dir, data, policy.target_dpi, policy.jpeg_quality).await.map(Some)
+            } else {
+                Ok(None)
+            }
+        } else {
+            let language = self
+                .db
+                .get_user_settings(document.user_id)
+                .await?
+                .map(|settings| settings.ocr_language)
+                .unwrap_or_else(|| "eng".to_string());
+            recompress_image(&work_dir, data, &document.mime_type, policy, &language).await
+        };
+        let _ = tokio::fs::remove_dir_all(&work_dir).await;
+        result
+    }
+
+    /// Delete the originals whose window ended; returns how many were deleted
+    pub async fn purge_expired_originals(&self) -> Result<usize> {
+        let expired = self.db.get_expired_compressed_originals(BATCH_SIZE).await?;
+        let mut purged = 0;
+        for (original, owner_id) in expired {
+            match self.delete_original_file(owner_id, &original).await {
+                Ok(()) => {
+                    self.db.mark_compressed_original_purged(original.document_id).await?;
+                    purged += 1;
+                }
+                Err(e) => warn!("Failed to delete the original of document {}: {}", original.document_id, e),
+            }
+        }
+        if purged > 0 {
+            info!("Deleted {} original(s) of recompressed documents", purged);
+        }
+        Ok(purged)
+    }
+
+    /// Delete the kept file of an original, e.g. with its document
+    pub async fn delete_original_file(&self, owner_id: Uuid, original: &CompressedOriginal) -> Result<()> {
+        let Some(file_path) = &original.file_path else {
+            return Ok(());
+        };
+        self.file_service
+            .delete_version_file(owner_id, original.id, &original.filename, file_path)
+            .await
+    }
+}
+
+/// Rewrite a PDF with its images downsampled to `dpi` and stored as JPEG. The text
+/// layer and vector content are kept. `GHOSTSCRIPT_PATH` overrides the `gs` binary and
+/// `STORAGE_COMPRESSION_TIMEOUT_SECONDS` bounds the run.
+async fn downsample_pdf(work_dir: &Path, data: &[u8], dpi: i32, jpeg_quality: i32) -> Result<Vec<u8>> {
+    let input = work_dir.join("input.pdf");
+    let output_path = work_dir.join("output.pdf");
+    tokio::fs::write(&input, data).await?;
+
+    let binary = std::env::var("GHOSTSCRIPT_PATH").unwrap_or_else(|_| "gs".to_string());
+    let timeout = timeout_seconds();
+    let output = SandboxedCommand::new(&binary)
+        .args(["-q", "-dBATCH", "-dNOPAUSE", "-dSAFER", "-sDEVICE=pdfwrite"])
+        .args(["-dDownsampleColorImages=true", "-dDownsampleGrayImages=true", "-dDownsampleMonoImages=true"])
+        .args(["-dColorImageDownsampleType=/Bicubic", "-dGrayImageDownsampleType=/Bicubic"])
+        .args(["-dAutoFilterColorImages=false", "-dColorImageFilter=/DCTEncode"])
+        .args(["-dAutoFilterGrayImages=false", "-dGrayImageFilter=/DCTEncode"])
+        .arg(format!("-dColorImageResolution={}", dpi))
+        .arg(format!("-dGrayImageResolution={}", dpi))
+        // Bilevel scans stay legible only at a higher resolution, and compress well anyway
+        .arg(format!("-dMonoImageResolution={}", (dpi * 2).min(600)))
+        .arg(format!("-dJPEGQ={}", jpeg_quality))
+        .arg(format!("-sOutputFile={}", output_path.display()))
+        .arg(&input)
+        .current_dir(work_dir)
+        .input(data)
+        .timeout(Duration::from_secs(timeout))
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Recompression failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    tokio::fs::read(&output_path)
+        .await
+        .map_err(|e| anyhow!("Recompression produced no PDF: {}", e))
+}
+
+fn timeout_seconds() -> u64 {
+    std::env::var("STORAGE_COMPRESSION_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECONDS)
+}
+
+/// Downsample an image to `target_dpi` and encode it as JPEG, wrapped in a PDF with a
+/// text layer when the policy asks for one. Multi-page TIFFs are left alone since only
+/// their first page would be kept.
+#[cfg(feature = "ocr")]
+async fn recompress_image(
+    work_dir: &Path,
+    data: &[u8],
+    mime_type: &str,
+    policy: &StorageCompressionPolicy,
+    language: &str,
+) -> Result<Option<Vec<u8>>> {
+    if mime_type == "image/tiff" && tiff_has_several_pages(data) {
+        return Ok(None);
+    }
+
+    let data = data.to_vec();
+    let (target_dpi, quality) = (policy.target_dpi, policy.jpeg_quality as u8);
+    let jpeg = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+        let image = image::load_from_memory(&data)?;
+        let image = match scale_for(image.width(), image.height(), target_dpi) {
+            Some((width, height)) => image.resize_exact(width, height, image::imageops::FilterType::Lanczos3),
+            None => image,
+        };
+        // JPEG has no alpha channel; grey scans stay single-channel
+        let image = if image.color().has_color() {
+            image::DynamicImage::ImageRgb8(image.to_rgb8())
+        } else {
+            image::DynamicImage::ImageLuma8(image.to_luma8())
+        };
+        let mut jpeg = Vec::new();
+        image.write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, quality))?;
+        Ok(jpeg)
+    })
+    .await??;
+
+    match policy.target_format {
+        CompressionFormat::Jpeg => Ok(Some(jpeg)),
+        CompressionFormat::Pdf => {
+            let page = work_dir.join("page.jpg");
+            tokio::fs::write(&page, &jpeg).await?;
+            crate::ocr::page_images::images_to_pdf(std::slice::from_ref(&page), language).await.map(Some)
+        }
+    }
+}
+
+#[cfg(not(feature = "ocr"))]
+async fn recompress_image(
+    _work_dir: &Path,
+    _data: &[u8],
+    _mime_type: &str,
+    _policy: &StorageCompressionPolicy,
+    _language: &str,
+) -> Result<Option<Vec<u8>>> {
+    Ok(None)
+}
+
+/// Size to downsample a page scan of `width` x `height` pixels to so it has
+/// `target_dpi`, or None when it is not above that already
+#[cfg_attr(not(feature = "ocr"), allow(dead_code))]
+fn scale_for(width: u32, height: u32, target_dpi: i32) -> Option<(u32, u32)> {
+    let long_edge = width.max(height) as f64;
+    let target_long_edge = PAGE_LONG_EDGE_INCHES * target_dpi as f64;
+    if long_edge <= target_long_edge {
+        return None;
+    }
+    let scale = target_long_edge / long_edge;
+    Some((
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    ))
+}
+
+/// Whether a TIFF has a second image file directory, i.e. more than one page
+#[cfg_attr(not(feature = "ocr"), allow(dead_code))]
+fn tiff_has_several_pages(data: &[u8]) -> bool {
+    let read_u16 = |at: usize, little: bool| -> Option<u64> {
+        let bytes: [u8; 2] = data.get(at..at + 2)?.try_into().ok()?;
+        Some(if little { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) } as u64)
+    };
+    let read_u32 = |at: usize, little: bool| -> Option<u64> {
+        let bytes: [u8; 4] = data.get(at..at + 4)?.try_into().ok()?;
+        Some(if little { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) } as u64)
+    };
+    let read_u64 = |at: usize, little: bool| -> Option<u64> {
+        let bytes: [u8; 8] = data.get(at..at + 8)?.try_into().ok()?;
+        Some(if little { u64::from_le_bytes(bytes) } else { u64::from_be_bytes(bytes) })
+    };
+
+    let little = match data.get(..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        // Not a TIFF; assume the worst
+        _ => return true,
+    };
+    let next_ifd = match read_u16(2, little) {
+        // Classic TIFF: 2-byte entry count, 12-byte entries, 4-byte next offset
+        Some(42) => read_u32(4, little).and_then(|ifd| {
+            let entries = read_u16(ifd as usize, little)?;
+            read_u32(ifd as usize + 2 + entries as usize * 12, little)
+        }),
+        // BigTIFF: 8-byte entry count, 20-byte entries, 8-byte next offset
+        Some(43) => read_u64(8, little).and_then(|ifd| {
+            let entries = read_u64(ifd as usize, little)?;
+            read_u64(ifd as usize + 8 + entries as usize * 20, little)
+        }),
+        _ => None,
+    };
+    next_ifd != Some(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_for_downsamples_to_target_resolution() {
+        // An A4 page scanned at 600 dpi
+        assert_eq!(scale_for(4960, 7014, 200), Some((1653, 2338)));
+        assert_eq!(scale_for(7014, 4960, 200), Some((2338, 1653)));
+        assert_eq!(scale_for(1654, 2338, 200), None);
+    }
+
+    #[test]
+    fn test_tiff_has_several_pages() {
+        // Little-endian header pointing at an IFD with no entries
+        let mut single = b"II*\0\x08\0\0\0".to_vec();
+        single.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        assert!(!tiff_has_several_pages(&single));
+
+        let mut several = b"II*\0\x08\0\0\0".to_vec();
+        several.extend_from_slice(&[0, 0, 14, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(tiff_has_several_pages(&several));
+
+        assert!(tiff_has_several_pages(b"not a tiff"));
+    }
+}
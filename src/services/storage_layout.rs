@@ -0,0 +1,255 @@
+use chrono::{DateTime, Datelike, Utc};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Longest a single rendered value may be, in characters
+const MAX_VALUE_LENGTH: usize = 100;
+
+/// Folder or name part used when a document has no value for a placeholder
+const MISSING_VALUE: &str = "none";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Year,
+    Month,
+    Day,
+    Correspondent,
+    DocumentType,
+    Title,
+    User,
+    Id,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+/// What a document contributes to its path in the storage layout
+#[derive(Debug, Clone)]
+pub struct LayoutFields<'a> {
+    pub document_id: Uuid,
+    pub filename: &'a str,
+    pub username: &'a str,
+    /// The original creation date when known, otherwise when the document was added
+    pub date: DateTime<Utc>,
+    pub correspondent: Option<&'a str>,
+    pub document_type: Option<&'a str>,
+}
+
+/// An admin's pattern like `{year}/{correspondent}/{title}-{id}.pdf` for where document
+/// files are stored under `LIBRARY_DIR`.
+///
+/// Placeholders are `{year}`, `{month}`, `{day}`, `{correspondent}`, `{document_type}`,
+/// `{title}` (the file name without extension), `{user}` and `{id}`, which every template
+/// needs so paths stay unique. `/` separates folders. A trailing `.ext` is only a hint:
+/// every file keeps its own extension.
+#[derive(Debug, Clone)]
+pub struct StoragePathTemplate {
+    template: String,
+    segments: Vec<Vec<Part>>,
+}
+
+impl StoragePathTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let template = template.trim();
+        if template.is_empty() {
+            return Err("The storage layout template must not be empty".to_string());
+        }
+        if template.starts_with('/') || template.contains('\\') {
+            return Err(format!("'{}' must be a relative path using '/'", template));
+        }
+
+        let mut segments = Vec::new();
+        let mut has_id = false;
+        for segment in template.split('/') {
+            let parts = parse_segment(segment, template)?;
+            if parts.is_empty() {
+                return Err(format!("'{}' has an empty folder name", template));
+            }
+            if let [Part::Literal(literal)] = parts.as_slice() {
+                if literal.trim_matches('.').is_empty() {
+                    return Err(format!("'{}' may not contain '.' or '..' folders", template));
+                }
+            }
+            has_id |= parts.contains(&Part::Field(Field::Id));
+            segments.push(parts);
+        }
+        if !has_id {
+            return Err(format!("'{}' needs {{id}} so every document gets its own path", template));
+        }
+
+        // The file's own extension replaces one written in the template
+        if let Some(Part::Literal(literal)) = segments.last_mut().and_then(|parts| parts.last_mut()) {
+            if let Some((stem, extension)) = literal.rsplit_once('.') {
+                if !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    *literal = stem.to_string();
+                }
+            }
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            segments,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// The path of a document's file relative to `LIBRARY_DIR`
+    pub fn render(&self, fields: &LayoutFields<'_>) -> String {
+        let mut path = self
+            .segments
+            .iter()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        Part::Literal(literal) => literal.clone(),
+                        Part::Field(field) => field_value(*field, fields),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let extension = Path::new(fields.filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .filter(|ext| ext.len() <= 10 && ext.chars().all(|c| c.is_ascii_alphanumeric()));
+        if let Some(extension) = extension {
+            path.push('.');
+            path.push_str(&extension.to_lowercase());
+        }
+        path
+    }
+}
+
+fn parse_segment(segment: &str, template: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut rest = segment;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(Part::Literal(rest[..start].to_string()));
+        }
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| format!("Unclosed placeholder in '{}'", template))?;
+        let field = match rest[start + 1..end].trim() {
+            "year" => Field::Year,
+            "month" => Field::Month,
+            "day" => Field::Day,
+            "correspondent" => Field::Correspondent,
+            "document_type" => Field::DocumentType,
+            "title" => Field::Title,
+            "user" => Field::User,
+            "id" => Field::Id,
+            other => return Err(format!("Unknown placeholder {{{}}} in '{}'", other, template)),
+        };
+        parts.push(Part::Field(field));
+        rest = &rest[end + 1..];
+    }
+    if rest.contains('}') {
+        return Err(format!("Unopened placeholder in '{}'", template));
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Literal(rest.to_string()));
+    }
+    Ok(parts)
+}
+
+fn field_value(field: Field, fields: &LayoutFields<'_>) -> String {
+    match field {
+        Field::Year => format!("{:04}", fields.date.year()),
+        Field::Month => format!("{:02}", fields.date.month()),
+        Field::Day => format!("{:02}", fields.date.day()),
+        Field::Id => fields.document_id.to_string(),
+        Field::Correspondent => sanitize(fields.correspondent.unwrap_or_default()),
+        Field::DocumentType => sanitize(fields.document_type.unwrap_or_default()),
+        Field::User => sanitize(fields.username),
+        Field::Title => sanitize(
+            Path::new(fields.filename)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(fields.filename),
+        ),
+    }
+}
+
+/// A value made safe to be a folder or file name on every backend
+fn sanitize(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .take(MAX_VALUE_LENGTH)
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').trim();
+    if cleaned.is_empty() {
+        MISSING_VALUE.to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn fields<'a>(document_id: Uuid, filename: &'a str, correspondent: Option<&'a str>) -> LayoutFields<'a> {
+        LayoutFields {
+            document_id,
+            filename,
+            username: "alice",
+            date: Utc.with_ymd_and_hms(2024, 3, 5, 10, 0, 0).unwrap(),
+            correspondent,
+            document_type: None,
+        }
+    }
+
+    #[test]
+    fn test_render_uses_the_files_extension() {
+        let template = StoragePathTemplate::parse("{year}/{correspondent}/{title}-{id}.pdf").unwrap();
+        let id = Uuid::new_v4();
+
+        assert_eq!(
+            template.render(&fields(id, "Invoice March.PDF", Some("ACME"))),
+            format!("2024/ACME/Invoice March-{}.pdf", id)
+        );
+        assert_eq!(
+            template.render(&fields(id, "scan.jpg", None)),
+            format!("2024/none/scan-{}.jpg", id)
+        );
+    }
+
+    #[test]
+    fn test_values_cannot_escape_their_folder() {
+        let template = StoragePathTemplate::parse("{user}/{year}-{month}-{day}/{correspondent}/{id}").unwrap();
+        let id = Uuid::new_v4();
+
+        let path = template.render(&fields(id, "notes", Some("../../etc/passwd")));
+        assert_eq!(path, format!("alice/2024-03-05/-..-etc-passwd/{}", id));
+        assert_eq!(template.render(&fields(id, "notes", Some(".."))), format!("alice/2024-03-05/none/{}", id));
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(StoragePathTemplate::parse("").is_err());
+        assert!(StoragePathTemplate::parse("{year}/{title}").is_err());
+        assert!(StoragePathTemplate::parse("/{year}/{id}").is_err());
+        assert!(StoragePathTemplate::parse("{year}//{id}").is_err());
+        assert!(StoragePathTemplate::parse("../{id}").is_err());
+        assert!(StoragePathTemplate::parse("{year}\\{id}").is_err());
+        assert!(StoragePathTemplate::parse("{owner}/{id}").is_err());
+        assert!(StoragePathTemplate::parse("{year/{id}").is_err());
+        assert!(StoragePathTemplate::parse("year}/{id}").is_err());
+    }
+}
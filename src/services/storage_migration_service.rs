@@ -0,0 +1,425 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::models::{
+    StorageBackendKind, StorageMigration, StorageMigrationFailure, StorageMigrationReport, StorageMigrationStatus,
+};
+use crate::services::file_service::FileService;
+use crate::storage::azure::{AzureBlobConfig, AZURE_PATH_PREFIX};
+use crate::storage::{self, factory, StorageBackend, StorageConfig};
+
+const DEFAULT_BATCH_SIZE: i32 = 50;
+const MAX_BATCH_SIZE: i32 = 1000;
+/// Page size used when counting documents left outside the target layout
+const RECONCILE_PAGE_SIZE: i64 = 1000;
+
+/// Source copies of documents moved by a migration that has not been reconciled yet,
+/// keyed by their new path. Reads that fail at the new path are served from the source.
+static DUAL_READ_PATHS: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Held while a migration runs in this process
+static RUN_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Source path to fall back to when reading `path` fails during a migration
+pub async fn dual_read_path(path: &str) -> Option<String> {
+    DUAL_READ_PATHS.read().await.get(path).cloned()
+}
+
+/// Moves document files into another storage backend or into the current backend's
+/// layout, in batches. Every copy is read back and compared by SHA-256 before the
+/// document is switched to it; source files stay in place until reconciliation passes.
+pub struct StorageMigrationService {
+    db: Database,
+    file_service: FileService,
+    config: Config,
+}
+
+impl StorageMigrationService {
+    pub fn new(db: Database, file_service: FileService, config: Config) -> Self {
+        Self { db, file_service, config }
+    }
+
+    pub fn batch_size(requested: Option<i32>) -> i32 {
+        requested
+            .or_else(|| std::env::var("STORAGE_MIGRATION_BATCH_SIZE").ok().and_then(|v| v.parse().ok()))
+            .unwrap_or(DEFAULT_BATCH_SIZE)
+            .clamp(1, MAX_BATCH_SIZE)
+    }
+
+    pub async fn target_backend(&self, target: StorageBackendKind) -> Result<Arc<dyn StorageBackend>> {
+        match target {
+            StorageBackendKind::Local => {
+                factory::create_storage_backend(StorageConfig::Local { upload_path: self.config.upload_path.clone() }).await
+            }
+            StorageBackendKind::Azure => {
+                let azure_config = AzureBlobConfig::from_env()?
+                    .ok_or_else(|| anyhow!("Migrating to Azure requires AZURE_STORAGE_ACCOUNT and AZURE_STORAGE_KEY"))?;
+                factory::create_storage_backend(StorageConfig::Azure { azure_config }).await
+            }
+            StorageBackendKind::S3 => {
+                #[cfg(feature = "s3")]
+                {
+                    let s3_config = self
+                        .config
+                        .s3_config
+                        .clone()
+                        .ok_or_else(|| anyhow!("Migrating to S3 requires S3_ENABLED=true and an S3 bucket configuration"))?;
+                    factory::create_storage_backend(StorageConfig::S3 { s3_config, fallback_path: None }).await
+                }
+                #[cfg(not(feature = "s3"))]
+                {
+                    Err(anyhow!("S3 support not compiled in"))
+                }
+            }
+        }
+    }
+
+    /// Continue a migration that was interrupted by a restart
+    pub async fn resume_interrupted(&self) -> Result<()> {
+        if let Some(migration) = self.db.get_running_storage_migration().await? {
+            info!("Resuming storage migration {} to {}", migration.id, migration.target_backend);
+            self.run(migration.id).await?;
+        }
+        Ok(())
+    }
+
+    /// Process the remaining batches of a migration, then reconcile
+    pub async fn run(&self, migration_id: Uuid) -> Result<StorageMigration> {
+        let Ok(_guard) = RUN_LOCK.try_lock() else {
+            return Err(anyhow!("A storage migration is already running"));
+        };
+
+        let migration = self
+            .db
+            .get_storage_migration(migration_id)
+            .await?
+            .ok_or_else(|| anyhow!("Storage migration {} not found", migration_id))?;
+        if migration.status != StorageMigrationStatus::Running {
+            return Ok(migration);
+        }
+
+        let result = match self.target_backend(migration.target_backend).await {
+            Ok(target) => match self.migrate_batches(&migration, target.as_ref()).await {
+                Ok(()) => self.reconcile(&migration, target.as_ref()).await,
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(report) => {
+                DUAL_READ_PATHS.write().await.clear();
+                info!(
+                    "Storage migration {} completed: {} verified, {} failed, {} mismatched, {} remaining",
+                    migration.id,
+                    report.verified,
+                    report.failed.len(),
+                    report.mismatched.len(),
+                    report.remaining
+                );
+                self.db
+                    .finish_storage_migration(migration.id, StorageMigrationStatus::Completed, Some(&report), None)
+                    .await
+            }
+            Err(e) => {
+                warn!("Storage migration {} failed: {}", migration.id, e);
+                self.db
+                    .finish_storage_migration(migration.id, StorageMigrationStatus::Failed, None, Some(&e.to_string()))
+                    .await
+            }
+        }
+    }
+
+    async fn migrate_batches(&self, migration: &StorageMigration, target: &dyn StorageBackend) -> Result<()> {
+        // A resumed run keeps serving reads from the sources it already copied
+        {
+            let mut paths = DUAL_READ_PATHS.write().await;
+            for (_, source_path, target_path, _) in self.db.get_migrated_storage_items(migration.id).await? {
+                paths.insert(target_path, source_path);
+            }
+        }
+
+        let documents_dir = self.file_service.get_documents_path();
+        let mut cursor = migration.last_document_id;
+
+        loop {
+            let batch = self
+                .db
+                .get_documents_for_storage_migration(cursor, migration.batch_size as i64)
+                .await?;
+            let Some(last_id) = batch.last().map(|d| d.0) else {
+                break;
+            };
+
+            let (mut migrated, mut skipped, mut failed) = (0, 0, 0);
+            for (document_id, user_id, filename, file_path, file_hash) in batch {
+                if in_target_layout(&file_path, document_id, user_id, migration.target_backend, &documents_dir) {
+                    skipped += 1;
+                    continue;
+                }
+
+                let error = match self
+                    .copy_verified(target, user_id, document_id, &filename, &file_path, file_hash.as_deref())
+                    .await
+                {
+                    Ok((target_path, sha256)) => {
+                        if self
+                            .db
+                            .complete_storage_migration_item(migration.id, document_id, &file_path, &target_path, &sha256)
+                            .await?
+                        {
+                            DUAL_READ_PATHS.write().await.insert(target_path, file_path);
+                            migrated += 1;
+                            continue;
+                        }
+                        "Document file changed while it was being copied".to_string()
+                    }
+                    Err(e) => e.to_string(),
+                };
+
+                warn!("Failed to migrate document {} ({}): {}", document_id, file_path, error);
+                self.db
+                    .fail_storage_migration_item(migration.id, document_id, &file_path, &error)
+                    .await?;
+                failed += 1;
+            }
+
+            self.db
+                .update_storage_migration_progress(migration.id, last_id, migrated, skipped, failed)
+                .await?;
+            info!(
+                "Storage migration {} batch done: {} migrated, {} already in place, {} failed",
+                migration.id, migrated, skipped, failed
+            );
+            cursor = Some(last_id);
+        }
+
+        Ok(())
+    }
+
+    /// Copy a document file into the target and read it back.
+    /// Returns the new path and the content hash.
+    async fn copy_verified(
+        &self,
+        target: &dyn StorageBackend,
+        user_id: Uuid,
+        document_id: Uuid,
+        filename: &str,
+        source_path: &str,
+        recorded_hash: Option<&str>,
+    ) -> Result<(String, String)> {
+        let data = self.file_service.read_file(source_path).await?;
+        let sha256 = format!("{:x}", Sha256::digest(&data));
+        if recorded_hash.is_some_and(|recorded| recorded != sha256) {
+            return Err(anyhow!("Source file does not match the document's recorded hash"));
+        }
+
+        let target_path = target.store_document(user_id, document_id, filename, &data).await?;
+        let stored = target.retrieve_file(&target_path).await?;
+        if format!("{:x}", Sha256::digest(&stored)) != sha256 {
+            return Err(anyhow!("Copy at {} failed hash verification", target_path));
+        }
+
+        Ok((target_path, sha256))
+    }
+
+    async fn reconcile(&self, migration: &StorageMigration, target: &dyn StorageBackend) -> Result<StorageMigrationReport> {
+        let items = self.db.get_migrated_storage_items(migration.id).await?;
+        let mut verified = 0;
+        let mut mismatched = Vec::new();
+        let mut confirmed = Vec::new();
+
+        for (document_id, source_path, target_path, current_path) in items {
+            // Deleted since it was migrated
+            let Some(current_path) = current_path else {
+                continue;
+            };
+
+            let problem = if current_path != target_path {
+                Some(format!("Document now points at {}", current_path))
+            } else if !target.file_exists(&target_path).await.unwrap_or(false) {
+                Some(format!("Target file {} is missing", target_path))
+            } else {
+                None
+            };
+
+            match problem {
+                Some(error) => mismatched.push(StorageMigrationFailure { document_id, source_path, error }),
+                None => {
+                    verified += 1;
+                    confirmed.push((document_id, source_path, target_path));
+                }
+            }
+        }
+
+        let failed = self
+            .db
+            .get_failed_storage_items(migration.id)
+            .await?
+            .into_iter()
+            .map(|(document_id, source_path, error)| StorageMigrationFailure { document_id, source_path, error })
+            .collect();
+
+        let documents_dir = self.file_service.get_documents_path();
+        let mut remaining = 0;
+        let mut cursor = None;
+        loop {
+            let page = self.db.get_documents_for_storage_migration(cursor, RECONCILE_PAGE_SIZE).await?;
+            let Some(last_id) = page.last().map(|d| d.0) else {
+                break;
+            };
+            remaining += page
+                .iter()
+                .filter(|(id, user_id, _, path, _)| {
+                    !in_target_layout(path, *id, *user_id, migration.target_backend, &documents_dir)
+                })
+                .count() as i64;
+            cursor = Some(last_id);
+        }
+
+        let mut source_files_deleted = 0;
+        if migration.delete_source && mismatched.is_empty() {
+            for (document_id, source_path, target_path) in confirmed {
+                // Only local source files are deleted; object storage keeps its copies
+                if source_path.starts_with("s3://") || source_path.starts_with(AZURE_PATH_PREFIX) || source_path == target_path {
+                    continue;
+                }
+                // A shared blob is only removed with its last reference
+                if storage::is_blob_path(&source_path) {
+                    match self.file_service.release_blob(&source_path).await {
+                        Ok(_) => {
+                            self.db.mark_storage_migration_source_deleted(migration.id, document_id).await?;
+                            source_files_deleted += 1;
+                        }
+                        Err(e) => warn!("Failed to release migrated blob {}: {}", source_path, e),
+                    }
+                    continue;
+                }
+                match self.file_service.resolve_file_path(&source_path).await {
+                    Ok(resolved) => match tokio::fs::remove_file(&resolved).await {
+                        Ok(()) => {
+                            self.db.mark_storage_migration_source_deleted(migration.id, document_id).await?;
+                            source_files_deleted += 1;
+                        }
+                        Err(e) => warn!("Failed to delete migrated source file {}: {}", resolved, e),
+                    },
+                    Err(e) => warn!("Migrated source file {} not found: {}", source_path, e),
+                }
+            }
+        } else if migration.delete_source {
+            warn!(
+                "Keeping source files of storage migration {}: {} documents did not reconcile",
+                migration.id,
+                mismatched.len()
+            );
+        }
+
+        Ok(StorageMigrationReport {
+            finished_at: Utc::now(),
+            verified,
+            mismatched,
+            failed,
+            remaining,
+            source_files_deleted,
+        })
+    }
+}
+
+/// Whether a stored path already follows the target backend's layout:
+/// `{upload_path}/documents/{document_id}.{ext}` locally, `documents/{user_id}/…/{document_id}.{ext}` in S3
+/// and `documents/{user_id}/{document_id}.{ext}` in Azure. Blobs and library files on the
+/// target backend are in place too.
+pub fn in_target_layout(
+    file_path: &str,
+    document_id: Uuid,
+    user_id: Uuid,
+    target: StorageBackendKind,
+    documents_dir: &Path,
+) -> bool {
+    // Blobs are shared by documents, and files in the library follow the storage layout
+    // template, so both are in place on the right backend
+    let in_library = match file_path.strip_prefix("s3://").or_else(|| file_path.strip_prefix(AZURE_PATH_PREFIX)) {
+        Some(key) => key.starts_with(&format!("{}/", storage::LIBRARY_DIR)),
+        None => documents_dir
+            .parent()
+            .is_some_and(|upload_dir| Path::new(file_path).starts_with(upload_dir.join(storage::LIBRARY_DIR))),
+    };
+    if storage::is_blob_path(file_path) || in_library {
+        return match target {
+            StorageBackendKind::S3 => file_path.starts_with("s3://"),
+            StorageBackendKind::Azure => file_path.starts_with(AZURE_PATH_PREFIX),
+            StorageBackendKind::Local => !file_path.contains("://"),
+        };
+    }
+
+    let path = Path::new(file_path);
+    let named_by_id = path.file_stem().and_then(|s| s.to_str()) == Some(document_id.to_string().as_str());
+
+    match target {
+        StorageBackendKind::S3 => named_by_id && file_path.starts_with(&format!("s3://documents/{}/", user_id)),
+        StorageBackendKind::Azure => {
+            named_by_id && file_path.starts_with(&format!("{}documents/{}/", AZURE_PATH_PREFIX, user_id))
+        }
+        StorageBackendKind::Local => named_by_id && !file_path.contains("://") && path.parent() == Some(documents_dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_target_layout() {
+        let document_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let documents_dir = Path::new("./uploads/documents");
+
+        let canonical_local = format!("./uploads/documents/{}.pdf", document_id);
+        let legacy_local = format!("./uploads/{}.pdf", Uuid::new_v4());
+        let canonical_s3 = format!("s3://documents/{}/2024/05/{}.pdf", user_id, document_id);
+
+        assert!(in_target_layout(&canonical_local, document_id, user_id, StorageBackendKind::Local, documents_dir));
+        assert!(!in_target_layout(&legacy_local, document_id, user_id, StorageBackendKind::Local, documents_dir));
+        assert!(!in_target_layout(&canonical_s3, document_id, user_id, StorageBackendKind::Local, documents_dir));
+
+        assert!(in_target_layout(&canonical_s3, document_id, user_id, StorageBackendKind::S3, documents_dir));
+        assert!(!in_target_layout(&canonical_local, document_id, user_id, StorageBackendKind::S3, documents_dir));
+        let other_user = format!("s3://documents/{}/2024/05/{}.pdf", Uuid::new_v4(), document_id);
+        assert!(!in_target_layout(&other_user, document_id, user_id, StorageBackendKind::S3, documents_dir));
+
+        let canonical_azure = format!("azure://documents/{}/{}.pdf", user_id, document_id);
+        assert!(in_target_layout(&canonical_azure, document_id, user_id, StorageBackendKind::Azure, documents_dir));
+        assert!(!in_target_layout(&canonical_s3, document_id, user_id, StorageBackendKind::Azure, documents_dir));
+        assert!(!in_target_layout(&canonical_azure, document_id, user_id, StorageBackendKind::Local, documents_dir));
+
+        let local_blob = "./uploads/blobs/ab/abcdef-1a2b3c4d.pdf";
+        let s3_blob = "s3://blobs/ab/abcdef-1a2b3c4d.pdf";
+        assert!(in_target_layout(local_blob, document_id, user_id, StorageBackendKind::Local, documents_dir));
+        assert!(!in_target_layout(local_blob, document_id, user_id, StorageBackendKind::S3, documents_dir));
+        assert!(in_target_layout(s3_blob, document_id, user_id, StorageBackendKind::S3, documents_dir));
+        assert!(!in_target_layout(s3_blob, document_id, user_id, StorageBackendKind::Azure, documents_dir));
+
+        let local_library = format!("./uploads/library/2024/ACME/Invoice-{}.pdf", document_id);
+        let s3_library = format!("s3://library/2024/ACME/Invoice-{}.pdf", document_id);
+        assert!(in_target_layout(&local_library, document_id, user_id, StorageBackendKind::Local, documents_dir));
+        assert!(!in_target_layout(&local_library, document_id, user_id, StorageBackendKind::S3, documents_dir));
+        assert!(in_target_layout(&s3_library, document_id, user_id, StorageBackendKind::S3, documents_dir));
+    }
+
+    #[test]
+    fn test_batch_size_is_clamped() {
+        assert_eq!(StorageMigrationService::batch_size(Some(0)), 1);
+        assert_eq!(StorageMigrationService::batch_size(Some(5000)), MAX_BATCH_SIZE);
+        assert_eq!(StorageMigrationService::batch_size(Some(20)), 20);
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{StorageQuotaSource, StorageUsage};
+
+const BYTES_PER_MB: i64 = 1024 * 1024;
+
+/// A file refused because it does not fit in its owner's storage quota
+#[derive(Debug)]
+pub struct StorageQuotaExceeded {
+    pub filename: String,
+    pub file_size: i64,
+    pub used_bytes: i64,
+    pub quota_bytes: i64,
+}
+
+impl std::fmt::Display for StorageQuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Storage quota exceeded: {} ({}) does not fit, {} of {} used",
+            self.filename,
+            format_mb(self.file_size),
+            format_mb(self.used_bytes),
+            format_mb(self.quota_bytes)
+        )
+    }
+}
+
+impl std::error::Error for StorageQuotaExceeded {}
+
+pub fn format_mb(bytes: i64) -> String {
+    format!("{:.1} MB", bytes as f64 / BYTES_PER_MB as f64)
+}
+
+/// The quota of users without an override, from `DEFAULT_STORAGE_QUOTA_MB`; unlimited
+/// when unset or 0
+pub fn default_quota_bytes() -> Option<i64> {
+    std::env::var("DEFAULT_STORAGE_QUOTA_MB")
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|mb| *mb > 0)
+        .map(|mb| mb.saturating_mul(BYTES_PER_MB))
+}
+
+pub fn mb_to_bytes(mb: i64) -> i64 {
+    mb.saturating_mul(BYTES_PER_MB)
+}
+
+/// Storage consumption of users measured against their quotas
+pub struct StorageQuotaService {
+    db: Database,
+}
+
+impl StorageQuotaService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    pub async fn usage(&self, user_id: Uuid) -> Result<StorageUsage> {
+        let (used_bytes, document_count) = self.db.get_user_storage_used(user_id).await?;
+        let (quota_bytes, quota_source) = match self.db.get_user_storage_quota_override(user_id).await? {
+            Some(quota_bytes) => (quota_bytes, StorageQuotaSource::Override),
+            None => (default_quota_bytes(), StorageQuotaSource::Default),
+        };
+
+        Ok(StorageUsage::new(user_id, used_bytes, document_count, quota_bytes, quota_source))
+    }
+
+    /// Refuses a file of `file_size` bytes that would take the user over their quota
+    pub async fn check(&self, user_id: Uuid, filename: &str, file_size: i64) -> Result<Option<StorageQuotaExceeded>> {
+        // Without a default and overrides nothing is limited; skip the sum
+        if default_quota_bytes().is_none() && self.db.get_user_storage_quota_override(user_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        let usage = self.usage(user_id).await?;
+        if !usage.would_exceed(file_size) {
+            return Ok(None);
+        }
+        Ok(Some(StorageQuotaExceeded {
+            filename: filename.to_string(),
+            file_size,
+            used_bytes: usage.used_bytes,
+            quota_bytes: usage.quota_bytes.unwrap_or_default(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_exceeded_message() {
+        let error = StorageQuotaExceeded {
+            filename: "scan.pdf".to_string(),
+            file_size: 3 * BYTES_PER_MB,
+            used_bytes: 99 * BYTES_PER_MB,
+            quota_bytes: 100 * BYTES_PER_MB,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Storage quota exceeded: scan.pdf (3.0 MB) does not fit, 99.0 MB of 100.0 MB used"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{RelocationCandidate, StorageMigrationFailure, StorageMigrationStatus, StorageRelocation};
+use crate::services::file_service::FileService;
+use crate::services::storage_layout::{LayoutFields, StoragePathTemplate};
+use crate::storage;
+
+const BATCH_SIZE: i64 = 100;
+
+/// Held while a relocation runs in this process
+static RUN_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// The path of a document relative to `LIBRARY_DIR` under `template`
+pub fn layout_path(template: &StoragePathTemplate, document: &RelocationCandidate) -> String {
+    template.render(&LayoutFields {
+        document_id: document.id,
+        filename: &document.filename,
+        username: &document.username,
+        date: document.original_created_at.unwrap_or(document.created_at),
+        correspondent: document.correspondent.as_deref(),
+        document_type: document.document_type.as_deref(),
+    })
+}
+
+/// Moves existing document files into the storage layout after its template changed,
+/// or back into the backend's own layout once it was removed. Each file is copied and
+/// verified before the document is switched to it and the old file is deleted.
+pub struct StorageRelocationService {
+    db: Database,
+    file_service: FileService,
+}
+
+impl StorageRelocationService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        Self { db, file_service }
+    }
+
+    /// Continue a relocation that was interrupted by a restart
+    pub async fn resume_interrupted(&self) -> Result<()> {
+        if let Some(relocation) = self.db.get_running_storage_relocation().await? {
+            info!("Resuming storage relocation {}", relocation.id);
+            self.run(relocation.id).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn run(&self, relocation_id: Uuid) -> Result<StorageRelocation> {
+        let Ok(_guard) = RUN_LOCK.try_lock() else {
+            return Err(anyhow!("A storage relocation is already running"));
+        };
+
+        let relocation = self
+            .db
+            .get_storage_relocation(relocation_id)
+            .await?
+            .ok_or_else(|| anyhow!("Storage relocation {} not found", relocation_id))?;
+        if relocation.status != StorageMigrationStatus::Running {
+            return Ok(relocation);
+        }
+
+        match self.relocate_batches(&relocation).await {
+            Ok(()) => {
+                info!("Storage relocation {} completed", relocation.id);
+                self.db
+                    .finish_storage_relocation(relocation.id, StorageMigrationStatus::Completed, None)
+                    .await
+            }
+            Err(e) => {
+                warn!("Storage relocation {} failed: {}", relocation.id, e);
+                self.db
+                    .finish_storage_relocation(relocation.id, StorageMigrationStatus::Failed, Some(&e.to_string()))
+                    .await
+            }
+        }
+    }
+
+    async fn relocate_batches(&self, relocation: &StorageRelocation) -> Result<()> {
+        let template = relocation
+            .template
+            .as_deref()
+            .map(StoragePathTemplate::parse)
+            .transpose()
+            .map_err(|e| anyhow!(e))?;
+        let mut cursor = relocation.last_document_id;
+
+        loop {
+            let batch = self.db.get_documents_for_storage_relocation(cursor, BATCH_SIZE).await?;
+            let Some(last_id) = batch.last().map(|document| document.id) else {
+                break;
+            };
+
+            let (mut moved, mut skipped, mut failures) = (0, 0, Vec::new());
+            for document in batch {
+                match self.relocate(&document, template.as_ref()).await {
+                    Ok(true) => moved += 1,
+                    Ok(false) => skipped += 1,
+                    Err(e) => {
+                        warn!("Failed to relocate document {} ({}): {}", document.id, document.file_path, e);
+                        failures.push(StorageMigrationFailure {
+                            document_id: document.id,
+                            source_path: document.file_path,
+                            error: e.to_string(),
+                        });
+                    }
+                }
+            }
+
+            self.db
+                .update_storage_relocation_progress(relocation.id, last_id, moved, skipped, &failures)
+                .await?;
+            info!(
+                "Storage relocation {} batch done: {} moved, {} already in place, {} failed",
+                relocation.id,
+                moved,
+                skipped,
+                failures.len()
+            );
+            cursor = Some(last_id);
+        }
+
+        Ok(())
+    }
+
+    /// Move one document's file where the template puts it. False when it is already
+    /// there or is a shared blob, which stays where it is.
+    async fn relocate(&self, document: &RelocationCandidate, template: Option<&StoragePathTemplate>) -> Result<bool> {
+        if storage::is_blob_path(&document.file_path) {
+            return Ok(false);
+        }
+
+        let relative_path = template.map(|template| layout_path(template, document));
+        let in_place = match &relative_path {
+            Some(relative_path) => {
+                self.file_service.is_library_path(&document.file_path)
+                    && document.file_path.ends_with(&format!("{}/{}", storage::LIBRARY_DIR, relative_path))
+            }
+            None => !self.file_service.is_library_path(&document.file_path),
+        };
+        if in_place {
+            return Ok(false);
+        }
+
+        let target_path = self
+            .file_service
+            .copy_document_file(
+                document.user_id,
+                document.id,
+                &document.filename,
+                &document.file_path,
+                relative_path.as_deref(),
+            )
+            .await?;
+        if !self.db.relocate_document_file(document.id, &document.file_path, &target_path).await? {
+            if let Err(e) = self.file_service.delete_stored_file(&target_path).await {
+                warn!("Failed to remove unused copy {}: {}", target_path, e);
+            }
+            return Err(anyhow!("Document file changed while it was being moved"));
+        }
+
+        if let Err(e) = self.file_service.delete_stored_file(&document.file_path).await {
+            warn!("Relocated document {} but failed to delete {}: {}", document.id, document.file_path, e);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_layout_path_prefers_the_original_date() {
+        let template = StoragePathTemplate::parse("{year}/{document_type}/{title}-{id}").unwrap();
+        let mut document = RelocationCandidate {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            username: "alice".to_string(),
+            filename: "lease.pdf".to_string(),
+            file_path: "./uploads/documents/lease.pdf".to_string(),
+            created_at: Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap(),
+            original_created_at: Some(Utc.with_ymd_and_hms(2019, 6, 1, 0, 0, 0).unwrap()),
+            correspondent: None,
+            document_type: Some("Contract".to_string()),
+        };
+
+        assert_eq!(layout_path(&template, &document), format!("2019/Contract/lease-{}.pdf", document.id));
+        document.original_created_at = None;
+        assert_eq!(layout_path(&template, &document), format!("2025/Contract/lease-{}.pdf", document.id));
+    }
+}
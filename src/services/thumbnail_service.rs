@@ -0,0 +1,148 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::UserRole;
+use crate::services::file_service::FileService;
+
+/// Pause between looks at the queue while it is empty or OCR has work waiting
+const IDLE_SECONDS: u64 = 10;
+/// Attempts before a thumbnail is left to be generated on first view
+const MAX_ATTEMPTS: i32 = 3;
+
+/// Generates the thumbnails of new documents, and the PDF previews of office documents
+/// they are rendered from, in the background after ingestion. Jobs are only claimed
+/// while no document waits for OCR.
+pub struct ThumbnailService {
+    db: Database,
+    file_service: FileService,
+    concurrency: usize,
+}
+
+impl ThumbnailService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        let concurrency = std::env::var("THUMBNAIL_WORKERS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(2)
+            .max(1);
+        Self { db, file_service, concurrency }
+    }
+
+    /// Whether thumbnails are generated ahead of the first view. Rendering them needs
+    /// the OCR feature.
+    pub fn enabled() -> bool {
+        cfg!(feature = "ocr")
+            && std::env::var("THUMBNAIL_PREGENERATION_ENABLED")
+                .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+                .unwrap_or(true)
+    }
+
+    pub async fn run(self) {
+        if !Self::enabled() {
+            return;
+        }
+        loop {
+            match self.run_once().await {
+                Ok(0) => tokio::time::sleep(Duration::from_secs(IDLE_SECONDS)).await,
+                Ok(processed) => debug!("Processed {} thumbnail job(s)", processed),
+                Err(e) => {
+                    error!("Failed to generate thumbnails: {}", e);
+                    tokio::time::sleep(Duration::from_secs(IDLE_SECONDS)).await;
+                }
+            }
+        }
+    }
+
+    /// Work through one batch of due jobs unless OCR has work waiting; returns how many
+    /// jobs were claimed
+    pub async fn run_once(&self) -> Result<usize> {
+        if self.db.has_pending_ocr_jobs().await? {
+            return Ok(0);
+        }
+        let jobs = self.db.claim_thumbnail_jobs(self.concurrency as i64 * 4).await?;
+        let claimed = jobs.len();
+
+        stream::iter(jobs)
+            .for_each_concurrent(self.concurrency, |(document_id, attempt)| async move {
+                let result = match self.generate(document_id).await {
+                    Ok(()) => self.db.complete_thumbnail_job(document_id).await,
+                    Err(e) => {
+                        let retry = retry_delay(attempt);
+                        if retry.is_none() {
+                            warn!("Giving up on the thumbnail of document {}: {}", document_id, e);
+                        }
+                        self.db
+                            .fail_thumbnail_job(document_id, &e.to_string(), retry.map(|delay| delay.as_secs() as i64))
+                            .await
+                    }
+                };
+                if let Err(e) = result {
+                    warn!("Failed to record the thumbnail job of document {}: {}", document_id, e);
+                }
+            })
+            .await;
+
+        Ok(claimed)
+    }
+
+    async fn generate(&self, document_id: Uuid) -> Result<()> {
+        // Deleted since it was queued; its job went with it
+        let Some(document) = self.db.get_document_by_id(document_id, Uuid::nil(), UserRole::Admin).await? else {
+            return Ok(());
+        };
+        // The first page is shown as OCR sees it, turned upright
+        let transform = self
+            .db
+            .get_page_transforms(document.id)
+            .await?
+            .into_iter()
+            .find(|t| t.page_number == 1);
+        self.file_service
+            .get_or_generate_transformed_thumbnail(&document.file_path, &document.original_filename, &document.mime_type, transform.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    /// Queue every document without a thumbnail job, and the failed ones again
+    pub async fn backfill(db: &Database) -> Result<i64> {
+        let queued = db.backfill_thumbnail_jobs().await?;
+        info!("Queued {} document(s) for thumbnail generation", queued);
+        Ok(queued)
+    }
+}
+
+/// Queue a document's thumbnail for the background worker, after ingestion or after its
+/// content was replaced, so the next view does not wait for it
+pub async fn requeue_thumbnail(db: &Database, document_id: Uuid) {
+    if !ThumbnailService::enabled() {
+        return;
+    }
+    if let Err(e) = db.enqueue_thumbnail_job(document_id).await {
+        warn!("Failed to queue the thumbnail of document {}: {}", document_id, e);
+    }
+}
+
+/// Wait before the attempt after `attempt`, growing with each failure; None when no
+/// attempt is left
+fn retry_delay(attempt: i32) -> Option<Duration> {
+    if attempt >= MAX_ATTEMPTS {
+        return None;
+    }
+    Some(Duration::from_secs(60 * 4u64.pow(attempt.max(1) as u32 - 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_grows_until_attempts_run_out() {
+        assert_eq!(retry_delay(1), Some(Duration::from_secs(60)));
+        assert_eq!(retry_delay(2), Some(Duration::from_secs(240)));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), None);
+    }
+}
@@ -0,0 +1,338 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use crate::db::Database;
+use crate::models::{Document, DocumentTitle, GeneratedTitleResponse};
+use crate::services::event_service::EventService;
+use crate::services::filename_template::title_filename;
+use crate::services::legal_hold_service;
+use crate::services::llm::llm_service::{LLMService, PromptTemplate};
+
+/// Whether documents with scanner names get a title after OCR
+static ENABLED_AFTER_OCR: Lazy<bool> = Lazy::new(|| {
+    std::env::var("AUTO_TITLE_ENABLED")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+});
+
+/// Title mode after OCR, see `TITLE_MODES`
+static MODE_AFTER_OCR: Lazy<&'static str> = Lazy::new(|| {
+    let mode = std::env::var("AUTO_TITLE_MODE").unwrap_or_default();
+    crate::models::TITLE_MODES
+        .into_iter()
+        .find(|known| known.eq_ignore_ascii_case(mode.trim()))
+        .unwrap_or("auto")
+});
+
+/// Characters of a document's text shown to the LLM; titles come from the first pages
+const LLM_TEXT_CHARS: usize = 4000;
+/// Lines at the start of the text the heuristic picks a title from
+const HEURISTIC_LINES: usize = 25;
+const MAX_TITLE_CHARS: usize = 80;
+
+const LLM_SYSTEM_PROMPT: &str = "You name scanned documents. Propose a short, specific title of at most \
+    eight words in the language of the document, such as \"ACME invoice 2024-117\" or \"Tenancy agreement \
+    Main Street 5\". Answer with JSON only, in this form: {\"title\": \"<title>\"}. Use null when the text \
+    does not say what the document is.";
+const LLM_PROMPT: PromptTemplate = PromptTemplate::new("title", 1);
+
+/// Words of names that scanners, cameras and phones give files, which say nothing
+/// about the document
+const GENERIC_NAME_WORDS: [&str; 24] = [
+    "scan", "scanned", "scans", "img", "image", "dsc", "dscn", "dcim", "pxl", "photo", "doc", "document", "file",
+    "page", "untitled", "unnamed", "copy", "new", "print", "output", "mfp", "cam", "pdf", "screenshot",
+];
+
+/// Words of lines that name the kind of a document, which make good titles
+const TITLE_KEYWORDS: [&str; 30] = [
+    "invoice", "receipt", "contract", "agreement", "statement", "letter", "certificate", "policy", "report",
+    "notice", "reminder", "offer", "quotation", "order", "payslip", "minutes", "application", "confirmation",
+    "rechnung", "vertrag", "bescheid", "kündigung", "mahnung", "angebot", "quittung", "bestätigung",
+    "abrechnung", "facture", "contrat", "attestation",
+];
+
+/// Proposes human-readable titles for documents from their text, with the LLM or a
+/// heuristic, and renames them. The name a document had before is kept, so a title
+/// can be undone. Renames stay in Readur; files on bidirectional sources keep their names.
+pub struct TitleService {
+    db: Database,
+    llm_service: LLMService,
+}
+
+impl TitleService {
+    pub fn new(db: Database) -> Self {
+        let llm_service = LLMService::new(db.get_pool().clone());
+        Self { db, llm_service }
+    }
+
+    /// Whether documents with scanner names get a title after OCR
+    pub fn enabled_after_ocr() -> bool {
+        *ENABLED_AFTER_OCR
+    }
+
+    /// Whether the `llm` mode can be used
+    pub fn llm_available(&self) -> bool {
+        self.llm_service.chat_enabled()
+    }
+
+    /// A title for the document's text, and what made it; None when the text has no
+    /// line that would do
+    pub async fn propose(&self, document: &Document, mode: &str) -> Result<Option<(String, &'static str)>> {
+        let text = document_text(document)
+            .ok_or_else(|| anyhow!("Document {} has no text to take a title from", document.id))?;
+
+        let title = match mode {
+            "heuristic" => heuristic_title(text).map(|title| (title, "heuristic")),
+            "llm" => self.title_with_llm(text).await?.map(|title| (title, "llm")),
+            _ if self.llm_available() => match self.title_with_llm(text).await {
+                Ok(Some(title)) => Some((title, "llm")),
+                Ok(None) => heuristic_title(text).map(|title| (title, "heuristic")),
+                Err(e) => {
+                    warn!("LLM title generation failed for document {}, using the heuristic: {}", document.id, e);
+                    heuristic_title(text).map(|title| (title, "heuristic"))
+                }
+            },
+            _ => heuristic_title(text).map(|title| (title, "heuristic")),
+        };
+        Ok(title)
+    }
+
+    /// Generate a title and, unless `dry_run`, rename the document to it
+    pub async fn generate(&self, document: &Document, mode: &str, dry_run: bool) -> Result<Option<GeneratedTitleResponse>> {
+        let Some((title, generated_by)) = self.propose(document, mode).await? else {
+            return Ok(None);
+        };
+        let filename = title_filename(&title, &document.original_filename);
+        let applied = !dry_run && filename != document.original_filename;
+        if applied {
+            legal_hold_service::ensure_not_held(&self.db, document.id).await?;
+            self.db
+                .upsert_document_title(document.id, &document.original_filename, &title, generated_by)
+                .await?;
+            let renamed = self.db.rename_document(document.id, &filename, None).await?;
+            EventService::new(self.db.clone())
+                .publish_document_updated(&renamed, &["filename"])
+                .await;
+            info!("Document {} retitled '{}' ({})", document.id, filename, generated_by);
+        }
+
+        Ok(Some(GeneratedTitleResponse {
+            title,
+            filename,
+            generated_by: generated_by.to_string(),
+            applied,
+        }))
+    }
+
+    /// Title a document of a bulk retitle; documents with meaningful names are left
+    /// alone unless `include_named`
+    pub async fn retitle(&self, document: &Document, mode: &str, include_named: bool) -> Result<()> {
+        if !include_named && !looks_generic(&document.original_filename) {
+            return Ok(());
+        }
+        self.generate(document, mode, false)
+            .await?
+            .ok_or_else(|| anyhow!("No title found in the text of document {}", document.id))?;
+        Ok(())
+    }
+
+    /// Title a freshly processed document when its name looks like scanner output and it
+    /// was never titled before
+    pub async fn retitle_after_ocr(&self, document: &Document) -> Result<()> {
+        if !looks_generic(&document.original_filename) {
+            return Ok(());
+        }
+        if self.db.get_document_title(document.id).await?.is_some() {
+            return Ok(());
+        }
+        if self.generate(document, *MODE_AFTER_OCR, false).await?.is_none() {
+            debug!("No title found in the text of document {}", document.id);
+        }
+        Ok(())
+    }
+
+    /// Give the document back the name it had before its first generated title
+    pub async fn restore(&self, document: &Document) -> Result<Option<(DocumentTitle, Document)>> {
+        let Some(title) = self.db.get_document_title(document.id).await? else {
+            return Ok(None);
+        };
+        legal_hold_service::ensure_not_held(&self.db, document.id).await?;
+        let renamed = self.db.rename_document(document.id, &title.original_filename, None).await?;
+        self.db.delete_document_title(document.id).await?;
+        EventService::new(self.db.clone())
+            .publish_document_updated(&renamed, &["filename"])
+            .await;
+        Ok(Some((title, renamed)))
+    }
+
+    async fn title_with_llm(&self, text: &str) -> Result<Option<String>> {
+        let excerpt: String = text.chars().take(LLM_TEXT_CHARS).collect();
+        let answer = self
+            .llm_service
+            .complete(LLM_PROMPT, LLM_SYSTEM_PROMPT, &excerpt)
+            .await
+            .map_err(|e| anyhow!(e))?;
+        Ok(parse_llm_answer(&answer))
+    }
+}
+
+/// OCR text, or the content of documents that have no OCR text
+fn document_text(document: &Document) -> Option<&str> {
+    let usable = |text: &&str| !text.trim().is_empty();
+    document
+        .ocr_text
+        .as_deref()
+        .filter(usable)
+        .or_else(|| document.content.as_deref().filter(usable))
+}
+
+/// Whether a file name says nothing about the document, like `SCAN_0001.pdf`,
+/// `IMG_20240105_103321.jpg`, `Scan 2024-01-05.pdf` or a UUID
+pub fn looks_generic(filename: &str) -> bool {
+    let stem = match filename.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => filename,
+    };
+    let hex = stem.chars().filter(|c| *c != '-').collect::<String>();
+    if hex.len() >= 32 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return true;
+    }
+
+    stem.split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .all(|word| GENERIC_NAME_WORDS.contains(&word.to_lowercase().as_str()))
+}
+
+/// The line near the start of the text that most looks like a heading: a few words,
+/// mostly letters, preferably naming the kind of document
+pub fn heuristic_title(text: &str) -> Option<String> {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .take(HEURISTIC_LINES)
+        .enumerate()
+        .filter_map(|(position, line)| {
+            // Earlier lines win ties
+            line_score(&line).map(|score| (score - position as f32 * 0.05, line))
+        })
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .and_then(|(_, line)| clean_title(&line))
+}
+
+fn line_score(line: &str) -> Option<f32> {
+    let words = line.split_whitespace().count();
+    let chars = line.chars().count();
+    if !(2..=12).contains(&words) || !(6..=120).contains(&chars) {
+        return None;
+    }
+    let lowercase = line.to_lowercase();
+    if lowercase.contains('@') || lowercase.contains("www.") || lowercase.contains("http") {
+        return None;
+    }
+    // Phone numbers, IBANs and reference numbers
+    if line.chars().filter(char::is_ascii_digit).count() > 8 {
+        return None;
+    }
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count() as f32 / chars as f32;
+    if letters < 0.6 {
+        return None;
+    }
+
+    let mut score = letters;
+    if lowercase
+        .split(|c: char| !c.is_alphabetic())
+        .any(|word| TITLE_KEYWORDS.iter().any(|keyword| word.starts_with(keyword)))
+    {
+        score += 2.0;
+    }
+    if line.chars().filter(|c| c.is_alphabetic()).all(char::is_uppercase) {
+        score += 0.5;
+    }
+    Some(score)
+}
+
+/// A title without surrounding punctuation or quotes, path separators or runs of
+/// whitespace, cut at a word to `MAX_TITLE_CHARS`
+fn clean_title(title: &str) -> Option<String> {
+    let title: String = title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .map(|c| if c == '/' || c == '\\' { '-' } else { c })
+        .collect();
+    let title = title.trim_matches(|c: char| c.is_whitespace() || "\"'`-:;,.|*#".contains(c));
+
+    let mut cut = String::new();
+    for word in title.split(' ') {
+        let length = cut.chars().count() + word.chars().count() + usize::from(!cut.is_empty());
+        if length > MAX_TITLE_CHARS {
+            break;
+        }
+        if !cut.is_empty() {
+            cut.push(' ');
+        }
+        cut.push_str(word);
+    }
+    (!cut.is_empty()).then_some(cut)
+}
+
+/// The title of the LLM's JSON answer, or of a plain-text one
+fn parse_llm_answer(answer: &str) -> Option<String> {
+    let answer = answer.trim();
+    match serde_json::from_str::<Value>(answer) {
+        Ok(value) => value.get("title").and_then(Value::as_str).and_then(clean_title),
+        Err(_) => answer.lines().next().and_then(clean_title),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generic_names() {
+        assert!(looks_generic("SCAN_0001.pdf"));
+        assert!(looks_generic("IMG_20240105_103321.jpg"));
+        assert!(looks_generic("Scan 2024-01-05 10.33.21.pdf"));
+        assert!(looks_generic("DSC01234.JPG"));
+        assert!(looks_generic("document(1).pdf"));
+        assert!(looks_generic("20240105.pdf"));
+        assert!(looks_generic("3f2a9c1e-7b4d-4e8f-9a0b-1c2d3e4f5a6b.pdf"));
+        assert!(!looks_generic("ACME invoice March.pdf"));
+        assert!(!looks_generic("Mietvertrag.pdf"));
+    }
+
+    #[test]
+    fn test_heuristic_prefers_document_kind() {
+        let text = "ACME Corporation\nMain Street 5, 12345 Springfield\nTel. +49 30 1234567 89\n\
+                    info@acme.example\n\nInvoice for March 2024\nDate: 2024-03-01\n";
+        assert_eq!(heuristic_title(text).as_deref(), Some("Invoice for March 2024"));
+    }
+
+    #[test]
+    fn test_heuristic_without_keywords_takes_an_early_heading() {
+        let text = "   \n12\nAnnual meeting of the garden club\nThe meeting opened at seven in the evening with all members present.";
+        assert_eq!(heuristic_title(text).as_deref(), Some("Annual meeting of the garden club"));
+        assert_eq!(heuristic_title("1234 5678\n---\n"), None);
+    }
+
+    #[test]
+    fn test_clean_title() {
+        assert_eq!(clean_title("  \"Lease / Main   Street\". ").as_deref(), Some("Lease - Main Street"));
+        let long = "word ".repeat(40);
+        let cut = clean_title(&long).unwrap();
+        assert!(cut.chars().count() <= MAX_TITLE_CHARS);
+        assert!(cut.ends_with("word"));
+        assert_eq!(clean_title(" -- "), None);
+    }
+
+    #[test]
+    fn test_parse_llm_answer() {
+        assert_eq!(parse_llm_answer(r#"{"title": "ACME invoice 2024-117"}"#).as_deref(), Some("ACME invoice 2024-117"));
+        assert_eq!(parse_llm_answer(r#"{"title": null}"#), None);
+        assert_eq!(parse_llm_answer("Tenancy agreement\nExplanation"), Some("Tenancy agreement".to_string()));
+    }
+}
@@ -0,0 +1,170 @@
+//! Machine translation of document text, with a LibreTranslate-compatible API when
+//! one is configured and with the chat model otherwise.
+
+use futures::stream::{self, StreamExt, TryStreamExt};
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::db::Database;
+use crate::models::{language_name, ContentDestination, Document, TranslationProvider};
+use crate::ocr::PAGE_BREAK;
+use crate::services::data_residency;
+use crate::services::llm::llm_service::{LLMService, PromptTemplate};
+use crate::services::processing_usage;
+use crate::utils::text_chunks::split_text_chunks;
+
+/// `POST` endpoint of a LibreTranslate-compatible API, e.g. `http://libretranslate:5000/translate`
+static TRANSLATION_API_URL: Lazy<Option<String>> = Lazy::new(|| {
+    std::env::var("TRANSLATION_API_URL").ok().map(|url| url.trim().to_string()).filter(|url| !url.is_empty())
+});
+
+static TRANSLATION_API_KEY: Lazy<Option<String>> =
+    Lazy::new(|| std::env::var("TRANSLATION_API_KEY").ok().filter(|key| !key.trim().is_empty()));
+
+/// Longest document text that is translated, in characters
+pub const MAX_TRANSLATION_CHARS: usize = 50_000;
+
+/// Characters sent per request
+const CHUNK_CHARS: usize = 3000;
+
+/// Requests in flight for one document
+const CHUNK_CONCURRENCY: usize = 4;
+
+const API_TIMEOUT_SECONDS: u64 = 120;
+
+const LLM_SYSTEM_PROMPT: &str = "You translate documents. Answer with the translation only, without comments. \
+    Keep the line breaks, and keep names, numbers, amounts, dates and identifiers as written.";
+const LLM_PROMPT: PromptTemplate = PromptTemplate::new("translation", 1);
+
+#[derive(Debug, Deserialize)]
+struct ApiTranslation {
+    #[serde(rename = "translatedText")]
+    translated_text: String,
+}
+
+/// The translation of a text and how it was made
+#[derive(Debug)]
+pub struct Translated {
+    pub text: String,
+    pub provider: TranslationProvider,
+    pub model: Option<String>,
+    /// Requests made to the provider
+    pub requests: usize,
+}
+
+pub struct TranslationService {
+    db: Database,
+    llm_service: LLMService,
+    client: Client,
+}
+
+impl TranslationService {
+    pub fn new(db: &Database) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(API_TIMEOUT_SECONDS))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        Self { db: db.clone(), llm_service: LLMService::new(db.get_pool().clone()), client }
+    }
+
+    /// The LibreTranslate-compatible endpoint, when one is configured
+    pub fn api_url() -> Option<&'static str> {
+        TRANSLATION_API_URL.as_deref()
+    }
+
+    /// How translations are made, None when neither a translation API nor a chat
+    /// model is configured
+    pub fn provider(&self) -> Option<TranslationProvider> {
+        if TRANSLATION_API_URL.is_some() {
+            Some(TranslationProvider::Api)
+        } else if self.llm_service.chat_enabled() {
+            Some(TranslationProvider::Llm)
+        } else {
+            None
+        }
+    }
+
+    /// The text a document is translated from: its OCR text, or else its extracted text
+    pub fn document_text(document: &Document) -> Option<&str> {
+        let usable = |text: &&str| !text.trim().is_empty();
+        document
+            .ocr_text
+            .as_deref()
+            .filter(usable)
+            .or_else(|| document.content.as_deref().filter(usable))
+    }
+
+    /// Translate text into the language with the ISO 639-1 code, page by page so the
+    /// translation keeps the page breaks
+    pub async fn translate(&self, text: &str, language: &str) -> Result<Translated, String> {
+        let provider = self.provider().ok_or("No translation API or LLM is configured")?;
+        // Requests to the LLM are checked one by one
+        if let (TranslationProvider::Api, Some(url)) = (provider, Self::api_url()) {
+            let document = processing_usage::current_document();
+            data_residency::authorize(self.db.get_pool(), ContentDestination::Translation, url, document.as_slice(), text.len())
+                .await?;
+        }
+
+        let pages: Vec<Vec<&str>> = text.split(PAGE_BREAK).map(|page| split_text_chunks(page, CHUNK_CHARS)).collect();
+        // Owned chunks keep the stream free of borrows of `text`, so the handler's
+        // future stays `Send`
+        let chunks: Vec<String> = pages.iter().flatten().map(|chunk| chunk.to_string()).collect();
+        let requests = chunks.len();
+        let translated: Vec<String> = stream::iter(chunks)
+            .map(|chunk| async move { self.translate_chunk(provider, &chunk, language).await })
+            .buffered(CHUNK_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        let mut translated = translated.into_iter();
+        let pages: Vec<String> = pages
+            .iter()
+            .map(|page| translated.by_ref().take(page.len()).collect::<Vec<_>>().join("\n"))
+            .collect();
+
+        Ok(Translated {
+            text: pages.join(&PAGE_BREAK.to_string()),
+            provider,
+            model: (provider == TranslationProvider::Llm).then(|| self.llm_service.model().to_string()),
+            requests,
+        })
+    }
+
+    async fn translate_chunk(&self, provider: TranslationProvider, chunk: &str, language: &str) -> Result<String, String> {
+        match provider {
+            TranslationProvider::Llm => {
+                let prompt = format!("Translate into {}:\n\n{}", language_name(language), chunk);
+                self.llm_service.complete(LLM_PROMPT, LLM_SYSTEM_PROMPT, &prompt).await
+            }
+            TranslationProvider::Api => self.request_api_translation(chunk, language).await,
+        }
+    }
+
+    async fn request_api_translation(&self, chunk: &str, language: &str) -> Result<String, String> {
+        let url = TRANSLATION_API_URL.as_deref().ok_or("Translation API is not configured")?;
+        let response = self
+            .client
+            .post(url)
+            .json(&serde_json::json!({
+                "q": chunk,
+                "source": "auto",
+                "target": language,
+                "format": "text",
+                "api_key": TRANSLATION_API_KEY.as_deref(),
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach translation API: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Translation API returned error: {}", response.status()));
+        }
+        let translation: ApiTranslation = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse translation API response: {}", e))?;
+        Ok(translation.translated_text)
+    }
+}
@@ -0,0 +1,181 @@
+//! Time-based one-time passwords (RFC 6238) and recovery codes for two-factor
+//! login of local accounts. Codes are the authenticator app defaults: HMAC-SHA1,
+//! six digits, a new code every 30 seconds.
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+pub const ISSUER: &str = "Readur";
+const SECRET_BYTES: usize = 20;
+const STEP_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+/// Steps either side of the current one still accepted, for clock drift
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// A new random shared secret, base32 encoded as authenticator apps expect
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    base32_encode(&bytes)
+}
+
+/// URI to show as a QR code for authenticator apps to scan
+pub fn provisioning_uri(username: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = urlencoding::encode(ISSUER),
+        account = urlencoding::encode(username),
+        secret = secret,
+        digits = DIGITS,
+        period = STEP_SECONDS,
+    )
+}
+
+/// The step a code belongs to if it is valid at `unix_time` and newer than
+/// `last_used_step`, so each code works only once
+pub fn verify_code(secret: &str, code: &str, unix_time: i64, last_used_step: Option<i64>) -> Option<i64> {
+    let code = code.trim().replace(' ', "");
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let key = base32_decode(secret)?;
+    let current = unix_time.div_euclid(STEP_SECONDS);
+
+    (current - ALLOWED_DRIFT_STEPS..=current + ALLOWED_DRIFT_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| code_at_step(&key, *step) == code)
+}
+
+fn code_at_step(key: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // Dynamic truncation, RFC 4226 section 5.3
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+    format!("{:0width$}", binary % 10u32.pow(DIGITS), width = DIGITS as usize)
+}
+
+/// New single-use recovery codes, formatted `xxxx-xxxx-xxxx-xxxx`
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 8];
+            rand::rngs::OsRng.fill_bytes(&mut bytes);
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{}-{}-{}-{}", &hex[0..4], &hex[4..8], &hex[8..12], &hex[12..16])
+        })
+        .collect()
+}
+
+/// What is stored for a recovery code. Dashes, spaces and case are ignored so codes
+/// can be typed as they are read.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+/// Whether an entered second factor looks like a TOTP code rather than a recovery code
+pub fn is_totp_code(code: &str) -> bool {
+    let code = code.trim().replace(' ', "");
+    code.len() == DIGITS as usize && code.chars().all(|c| c.is_ascii_digit())
+}
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | u64::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for c in encoded.chars().filter(|c| *c != '=' && !c.is_whitespace()) {
+        let value = BASE32_ALPHABET.iter().position(|&a| a as char == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B test secret, "12345678901234567890" in ASCII
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_base32_round_trip() {
+        assert_eq!(base32_encode(b"12345678901234567890"), RFC_SECRET);
+        assert_eq!(base32_decode(RFC_SECRET).unwrap(), b"12345678901234567890");
+        assert_eq!(base32_decode(&RFC_SECRET.to_lowercase()).unwrap(), b"12345678901234567890");
+        assert!(base32_decode("not base32!").is_none());
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let key = base32_decode(RFC_SECRET).unwrap();
+        // The RFC lists eight digits; six-digit codes are their last six
+        assert_eq!(code_at_step(&key, 59 / 30), "287082");
+        assert_eq!(code_at_step(&key, 1111111109 / 30), "081804");
+        assert_eq!(code_at_step(&key, 1234567890 / 30), "005924");
+        assert_eq!(code_at_step(&key, 2000000000 / 30), "279037");
+    }
+
+    #[test]
+    fn test_verify_code_allows_drift_and_rejects_reuse() {
+        let now = 1111111109;
+        assert_eq!(verify_code(RFC_SECRET, "081804", now, None), Some(now / 30));
+        assert_eq!(verify_code(RFC_SECRET, "081 804", now + 30, None), Some(now / 30));
+        assert_eq!(verify_code(RFC_SECRET, "081804", now + 90, None), None);
+        assert_eq!(verify_code(RFC_SECRET, "081804", now, Some(now / 30)), None);
+        assert_eq!(verify_code(RFC_SECRET, "12345", now, None), None);
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(codes[0].len(), 19);
+        assert_ne!(codes[0], codes[1]);
+        assert_eq!(hash_recovery_code(&codes[0]), hash_recovery_code(&codes[0].to_uppercase().replace('-', " ")));
+        assert!(!is_totp_code(&codes[0]));
+        assert!(is_totp_code("123 456"));
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let uri = provisioning_uri("jane doe", "ABC");
+        assert_eq!(
+            uri,
+            "otpauth://totp/Readur:jane%20doe?secret=ABC&issuer=Readur&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Resumable uploads with the tus protocol (https://tus.io/protocols/resumable-upload).
+//!
+//! Received bytes are appended to a part file in the temp directory. A chunk that
+//! carries an `Upload-Checksum` is kept only if it arrived whole and matches; a chunk
+//! without one keeps whatever arrived before the connection dropped, so the client
+//! resumes from there.
+
+use anyhow::Result;
+use base64ct::{Base64, Encoding};
+use bytes::Bytes;
+use chrono::Utc;
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{models::ResumableUpload, AppState};
+
+/// The protocol version spoken, sent as `Tus-Resumable` and `Tus-Version`
+pub const TUS_VERSION: &str = "1.0.0";
+/// Protocol extensions supported, sent as `Tus-Extension`
+pub const TUS_EXTENSIONS: &str = "creation,termination,checksum,expiration";
+/// Algorithms accepted in `Upload-Checksum`, sent as `Tus-Checksum-Algorithm`
+pub const TUS_CHECKSUM_ALGORITHMS: &str = "sha1,sha256";
+
+const DEFAULT_EXPIRATION_HOURS: i64 = 24;
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Expired uploads removed per cleanup run
+const CLEANUP_BATCH_SIZE: i64 = 500;
+
+/// Uploads a chunk is being written to in this process
+static ACTIVE_UPLOADS: Lazy<std::sync::Mutex<HashSet<Uuid>>> = Lazy::new(|| std::sync::Mutex::new(HashSet::new()));
+
+/// Held while a chunk is written to or an upload is removed
+pub struct UploadLock(Uuid);
+
+impl Drop for UploadLock {
+    fn drop(&mut self) {
+        ACTIVE_UPLOADS.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.0);
+    }
+}
+
+/// None when another request is writing to the upload
+pub fn lock_upload(id: Uuid) -> Option<UploadLock> {
+    ACTIVE_UPLOADS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id)
+        .then_some(UploadLock(id))
+}
+
+/// How long an upload is kept without progress, from `UPLOAD_EXPIRATION_HOURS`
+pub fn expiration() -> chrono::Duration {
+    let hours = std::env::var("UPLOAD_EXPIRATION_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_EXPIRATION_HOURS)
+        .max(1);
+    chrono::Duration::hours(hours)
+}
+
+/// Parse `Upload-Metadata`: comma-separated `key base64value` pairs, the value optional
+pub fn parse_metadata(header: &str) -> Result<HashMap<String, String>, String> {
+    let mut metadata = HashMap::new();
+    for pair in header.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+        let mut parts = pair.splitn(2, ' ');
+        let key = parts.next().unwrap_or_default();
+        let value = match parts.next().map(str::trim) {
+            Some(encoded) if !encoded.is_empty() => {
+                let decoded = Base64::decode_vec(encoded).map_err(|_| format!("Metadata {} is not valid base64", key))?;
+                String::from_utf8(decoded).map_err(|_| format!("Metadata {} is not valid UTF-8", key))?
+            }
+            _ => String::new(),
+        };
+        metadata.insert(key.to_string(), value);
+    }
+    Ok(metadata)
+}
+
+/// Expected digest of a chunk, from `Upload-Checksum`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    Sha1(Vec<u8>),
+    Sha256(Vec<u8>),
+}
+
+impl Checksum {
+    /// Parse `algorithm base64digest`. Err when the algorithm is not supported or the
+    /// digest is not valid base64.
+    pub fn parse(header: &str) -> Result<Self, String> {
+        let (algorithm, encoded) = header
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| "Upload-Checksum must be `algorithm digest`".to_string())?;
+        let digest = Base64::decode_vec(encoded.trim()).map_err(|_| "Checksum is not valid base64".to_string())?;
+        match algorithm {
+            "sha1" => Ok(Checksum::Sha1(digest)),
+            "sha256" => Ok(Checksum::Sha256(digest)),
+            other => Err(format!("Unsupported checksum algorithm: {}", other)),
+        }
+    }
+
+    fn hasher(&self) -> ChunkHasher {
+        match self {
+            Checksum::Sha1(_) => ChunkHasher::Sha1(Sha1::new()),
+            Checksum::Sha256(_) => ChunkHasher::Sha256(Sha256::new()),
+        }
+    }
+
+    fn matches(&self, hasher: ChunkHasher) -> bool {
+        match (self, hasher) {
+            (Checksum::Sha1(expected), ChunkHasher::Sha1(hasher)) => hasher.finalize().as_slice() == expected.as_slice(),
+            (Checksum::Sha256(expected), ChunkHasher::Sha256(hasher)) => hasher.finalize().as_slice() == expected.as_slice(),
+            _ => false,
+        }
+    }
+}
+
+enum ChunkHasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+}
+
+impl ChunkHasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            ChunkHasher::Sha1(hasher) => hasher.update(data),
+            ChunkHasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+}
+
+/// Why a chunk was not appended
+#[derive(Debug)]
+pub enum AppendError {
+    /// `Upload-Offset` is not the number of bytes received so far
+    OffsetMismatch,
+    /// The chunk goes past `Upload-Length`
+    TooLarge,
+    /// The chunk does not match `Upload-Checksum`; nothing was kept
+    ChecksumMismatch,
+    /// The connection dropped; bytes received before are kept unless a checksum was sent
+    Interrupted(String),
+    Internal(anyhow::Error),
+}
+
+impl From<std::io::Error> for AppendError {
+    fn from(e: std::io::Error) -> Self {
+        AppendError::Internal(e.into())
+    }
+}
+
+pub struct UploadService {
+    state: Arc<AppState>,
+}
+
+impl UploadService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Where the bytes of an upload are collected
+    pub fn part_path(&self, id: Uuid) -> PathBuf {
+        self.state.file_service.get_temp_path().join("uploads").join(format!("{}.part", id))
+    }
+
+    /// Create the empty part file of a new upload
+    pub async fn create_part_file(&self, id: Uuid) -> Result<()> {
+        let path = self.part_path(id);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::File::create(&path).await?;
+        Ok(())
+    }
+
+    /// Append a chunk at `offset` and record the new offset. Returns the updated upload.
+    pub async fn append<S, E>(
+        &self,
+        upload: &ResumableUpload,
+        offset: i64,
+        mut chunks: S,
+        checksum: Option<Checksum>,
+    ) -> Result<ResumableUpload, AppendError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: std::fmt::Display,
+    {
+        if offset != upload.upload_offset {
+            return Err(AppendError::OffsetMismatch);
+        }
+
+        let start = offset as u64;
+        let remaining = (upload.upload_length - offset) as u64;
+        let mut file = tokio::fs::OpenOptions::new().write(true).open(self.part_path(upload.id)).await?;
+        // Drop bytes of an earlier chunk that were written but never recorded
+        file.set_len(start).await?;
+        file.seek(std::io::SeekFrom::Start(start)).await?;
+
+        let mut hasher = checksum.as_ref().map(Checksum::hasher);
+        let mut written: u64 = 0;
+        let mut interrupted = None;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    interrupted = Some(e.to_string());
+                    break;
+                }
+            };
+            if written + chunk.len() as u64 > remaining {
+                file.set_len(start).await?;
+                return Err(AppendError::TooLarge);
+            }
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&chunk);
+            }
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await?;
+
+        if let (Some(checksum), Some(hasher)) = (&checksum, hasher) {
+            if let Some(reason) = interrupted {
+                file.set_len(start).await?;
+                return Err(AppendError::Interrupted(reason));
+            }
+            if !checksum.matches(hasher) {
+                file.set_len(start).await?;
+                return Err(AppendError::ChecksumMismatch);
+            }
+        }
+        file.sync_all().await?;
+
+        let updated = self
+            .state
+            .db
+            .update_resumable_upload_offset(upload.id, offset + written as i64, Utc::now() + expiration())
+            .await
+            .map_err(AppendError::Internal)?;
+
+        match interrupted {
+            Some(reason) => Err(AppendError::Interrupted(reason)),
+            None => Ok(updated),
+        }
+    }
+
+    /// Remove the part file of an upload
+    pub async fn remove_part_file(&self, id: Uuid) {
+        let path = self.part_path(id);
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove upload part file {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Remove an upload and whatever was received of it
+    pub async fn discard(&self, id: Uuid) -> Result<()> {
+        self.remove_part_file(id).await;
+        self.state.db.delete_resumable_upload(id).await
+    }
+
+    /// Remove expired uploads every hour
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.cleanup_expired().await {
+                Ok(removed) if removed > 0 => info!("Removed {} abandoned upload(s)", removed),
+                Ok(_) => {}
+                Err(e) => error!("Failed to remove abandoned uploads: {}", e),
+            }
+        }
+    }
+
+    pub async fn cleanup_expired(&self) -> Result<usize> {
+        let mut removed = 0;
+        for upload in self.state.db.get_expired_resumable_uploads(CLEANUP_BATCH_SIZE).await? {
+            // A chunk is being written; the upload is no longer abandoned
+            let Some(_lock) = lock_upload(upload.id) else {
+                continue;
+            };
+            self.discard(upload.id).await?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metadata() {
+        let metadata = parse_metadata("filename c2Nhbi5wZGY=,filetype YXBwbGljYXRpb24vcGRm, is_confidential").unwrap();
+        assert_eq!(metadata.get("filename").map(String::as_str), Some("scan.pdf"));
+        assert_eq!(metadata.get("filetype").map(String::as_str), Some("application/pdf"));
+        assert_eq!(metadata.get("is_confidential").map(String::as_str), Some(""));
+        assert!(parse_metadata("filename not-base64!").is_err());
+    }
+
+    #[test]
+    fn test_checksum() {
+        let checksum = Checksum::parse("sha256 uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=").unwrap();
+        let mut hasher = checksum.hasher();
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        assert!(checksum.matches(hasher));
+
+        let mut hasher = checksum.hasher();
+        hasher.update(b"hello");
+        assert!(!checksum.matches(hasher));
+
+        assert!(Checksum::parse("md5 XrY7u+Ae7tCTyyK7j1rNww==").is_err());
+        assert!(Checksum::parse("sha1").is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use anyhow::Result;
+use chrono::Utc;
+#[cfg(feature = "ocr")]
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{LlmQuotaStatus, LLM_FEATURE_VISION_OCR};
+use crate::services::file_service::FileService;
+use crate::services::llm::llm_service::LLMService;
+
+/// Documents whose OCR confidence (0-100) is below this are sent to the vision model
+const DEFAULT_CONFIDENCE_THRESHOLD: f32 = 40.0;
+/// Documents with more pages than this are never transcribed by the vision model
+const DEFAULT_MAX_PAGES: usize = 10;
+/// Pages a user may send to the vision model per UTC day
+const DEFAULT_DAILY_PAGE_QUOTA: i64 = 100;
+/// Resolution used to rasterize PDF pages for transcription
+#[cfg(feature = "ocr")]
+const RENDER_DPI: u32 = 150;
+/// Vision transcriptions carry no confidence score; they are stored with this one
+pub const VISION_TRANSCRIPTION_CONFIDENCE: f32 = 80.0;
+
+#[cfg(feature = "ocr")]
+pub(crate) const TRANSCRIBE_PROMPT: &str = "Transcribe all text on this scanned document page exactly as written, \
+preserving line breaks and reading order. Output only the transcribed text, without commentary. \
+If the page contains no text, output nothing.";
+
+/// Text produced by the vision model for a whole document
+#[derive(Debug, Clone)]
+pub struct VisionTranscription {
+    pub text: String,
+    pub pages: Vec<i32>,
+    pub model: String,
+}
+
+/// Re-transcribes very poor scans with a vision-capable LLM when Tesseract
+/// confidence is too low, within a per-user daily page quota
+pub struct VisionFallbackService {
+    db: Database,
+    file_service: FileService,
+    llm_service: LLMService,
+    enabled: bool,
+    confidence_threshold: f32,
+    max_pages: usize,
+    daily_page_quota: Option<i64>,
+}
+
+impl VisionFallbackService {
+    pub fn new(db: Database, file_service: FileService) -> Self {
+        let llm_service = LLMService::new(db.get_pool().clone());
+        let enabled = std::env::var("OCR_VISION_FALLBACK_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let confidence_threshold = std::env::var("OCR_VISION_FALLBACK_CONFIDENCE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CONFIDENCE_THRESHOLD);
+        let max_pages = std::env::var("OCR_VISION_FALLBACK_MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_PAGES);
+        // 0 disables the quota
+        let daily_page_quota = std::env::var("OCR_VISION_FALLBACK_DAILY_PAGES")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_DAILY_PAGE_QUOTA);
+
+        Self {
+            db,
+            file_service,
+            llm_service,
+            enabled,
+            confidence_threshold,
+            max_pages,
+            daily_page_quota: Some(daily_page_quota).filter(|q| *q > 0),
+        }
+    }
+
+    /// Whether an OCR result of this type and confidence qualifies for the vision fallback
+    pub fn should_attempt(&self, mime_type: &str, confidence: f32) -> bool {
+        self.enabled
+            && self.llm_service.vision_enabled()
+            && confidence < self.confidence_threshold
+            && (mime_type.starts_with("image/") || mime_type == "application/pdf")
+    }
+
+    pub async fn quota_status(&self, user_id: Uuid) -> Result<LlmQuotaStatus> {
+        let start_of_day = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let used_today = self.db.get_llm_usage_since(user_id, LLM_FEATURE_VISION_OCR, start_of_day).await?;
+
+        Ok(LlmQuotaStatus {
+            feature: LLM_FEATURE_VISION_OCR.to_string(),
+            used_today,
+            daily_limit: self.daily_page_quota,
+            remaining_today: self.daily_page_quota.map(|limit| (limit - used_today).max(0)),
+        })
+    }
+
+    /// Transcribe every page of the document with the vision model.
+    /// Returns `Ok(None)` when the document is too long or the user's quota
+    /// cannot cover all of its pages.
+    #[cfg(feature = "ocr")]
+    pub async fn transcribe(
+        &self,
+        document_id: Uuid,
+        user_id: Uuid,
+        file_path: &str,
+        mime_type: &str,
+        ocr_confidence: f32,
+    ) -> Result<Option<VisionTranscription>> {
+        use crate::ocr::{page_images, PAGE_BREAK};
+
+        let Some(model) = self.llm_service.vision_model().map(str::to_string) else {
+            return Ok(None);
+        };
+
+        let remaining = self.quota_status(user_id).await?.remaining_today;
+        let page_budget = match remaining {
+            Some(remaining) => self.max_pages.min(remaining as usize),
+            None => self.max_pages,
+        };
+        if page_budget == 0 {
+            info!("Vision fallback quota exhausted for user {}, keeping OCR result for document {}", user_id, document_id);
+            return Ok(None);
+        }
+
+        let data = self.file_service.read_file(file_path).await?;
+        // Render one page past the budget to tell whether the document fits
+        let pages = page_images::render_pages(&data, mime_type, RENDER_DPI, page_budget + 1).await?;
+        if pages.is_empty() || pages.len() > page_budget {
+            info!(
+                "Document {} exceeds the vision fallback page budget ({} pages), keeping OCR result",
+                document_id, page_budget
+            );
+            return Ok(None);
+        }
+
+        let mut page_texts = Vec::with_capacity(pages.len());
+        let mut transcribed_pages = Vec::with_capacity(pages.len());
+        for (index, page) in pages.iter().enumerate() {
+            let page_number = index as i32 + 1;
+            let png = page_images::encode_png(page)?;
+
+            let text = match self.llm_service.describe_image(&png, TRANSCRIBE_PROMPT).await {
+                Ok(Some(text)) => text,
+                Ok(None) => return Ok(None),
+                Err(e) => {
+                    warn!("Vision transcription failed for document {} page {}: {}", document_id, page_number, e);
+                    return Ok(None);
+                }
+            };
+
+            // Every page sent counts against the quota, even if it came back empty
+            self.db.record_llm_usage(user_id, LLM_FEATURE_VISION_OCR, 1, Some(document_id)).await?;
+            page_texts.push(text.trim().to_string());
+            transcribed_pages.push(page_number);
+        }
+
+        for page_number in &transcribed_pages {
+            self.db.record_vision_fallback_page(document_id, *page_number, &model, Some(ocr_confidence)).await?;
+        }
+
+        info!(
+            "Vision model {} transcribed {} pages of document {} (OCR confidence {:.1}%)",
+            model, transcribed_pages.len(), document_id, ocr_confidence
+        );
+
+        Ok(Some(VisionTranscription {
+            // Pages are kept apart like in pdftotext output, so page numbers still resolve
+            text: page_texts.join(&PAGE_BREAK.to_string()),
+            pages: transcribed_pages,
+            model,
+        }))
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    pub async fn transcribe(
+        &self,
+        _document_id: Uuid,
+        _user_id: Uuid,
+        _file_path: &str,
+        _mime_type: &str,
+        _ocr_confidence: f32,
+    ) -> Result<Option<VisionTranscription>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CreateUser, UserRole};
+    use crate::test_utils::{document_helpers::create_test_document, TestContext};
+
+    fn service(ctx: &TestContext, daily_page_quota: Option<i64>) -> VisionFallbackService {
+        VisionFallbackService {
+            db: ctx.state.db.clone(),
+            file_service: (*ctx.state.file_service).clone(),
+            llm_service: LLMService::new(ctx.state.db.get_pool().clone()),
+            enabled: true,
+            confidence_threshold: DEFAULT_CONFIDENCE_THRESHOLD,
+            max_pages: DEFAULT_MAX_PAGES,
+            daily_page_quota,
+        }
+    }
+
+    async fn create_user(ctx: &TestContext) -> Uuid {
+        let user = ctx.state.db.create_user(CreateUser {
+            username: format!("vision_{}", Uuid::new_v4().simple()),
+            email: format!("vision_{}@example.com", Uuid::new_v4().simple()),
+            password: "password123".to_string(),
+            role: Some(UserRole::User),
+        }).await.unwrap();
+        user.id
+    }
+
+    #[tokio::test]
+    async fn test_should_attempt() {
+        let ctx = TestContext::new().await;
+        std::env::set_var("LLM_VISION_MODEL", "llava");
+        let mut vision = service(&ctx, None);
+
+        assert!(vision.should_attempt("image/png", 12.0));
+        assert!(vision.should_attempt("application/pdf", 39.9));
+        assert!(!vision.should_attempt("application/pdf", DEFAULT_CONFIDENCE_THRESHOLD));
+        assert!(!vision.should_attempt("text/plain", 12.0));
+
+        vision.enabled = false;
+        assert!(!vision.should_attempt("image/png", 12.0));
+    }
+
+    #[tokio::test]
+    async fn test_quota_status_counts_todays_pages() {
+        let ctx = TestContext::new().await;
+        let db = &ctx.state.db;
+        let user_id = create_user(&ctx).await;
+
+        db.record_llm_usage(user_id, LLM_FEATURE_VISION_OCR, 30, None).await.unwrap();
+        db.record_llm_usage(user_id, LLM_FEATURE_VISION_OCR, 50, None).await.unwrap();
+        db.record_llm_usage(user_id, "summaries", 10, None).await.unwrap();
+        sqlx::query(
+            "INSERT INTO llm_usage (user_id, feature, units, created_at) VALUES ($1, $2, 40, NOW() - INTERVAL '2 days')"
+        )
+        .bind(user_id)
+        .bind(LLM_FEATURE_VISION_OCR)
+        .execute(db.get_pool())
+        .await
+        .unwrap();
+
+        let status = service(&ctx, Some(100)).quota_status(user_id).await.unwrap();
+        assert_eq!(status.used_today, 80);
+        assert_eq!(status.daily_limit, Some(100));
+        assert_eq!(status.remaining_today, Some(20));
+
+        db.record_llm_usage(user_id, LLM_FEATURE_VISION_OCR, 30, None).await.unwrap();
+        let status = service(&ctx, Some(100)).quota_status(user_id).await.unwrap();
+        assert_eq!(status.used_today, 110);
+        assert_eq!(status.remaining_today, Some(0));
+
+        let status = service(&ctx, None).quota_status(user_id).await.unwrap();
+        assert_eq!(status.remaining_today, None);
+    }
+
+    #[tokio::test]
+    async fn test_vision_fallback_pages_are_recorded_once_per_page() {
+        let ctx = TestContext::new().await;
+        let db = &ctx.state.db;
+        let user_id = create_user(&ctx).await;
+        let document = db.create_document(create_test_document(user_id)).await.unwrap();
+
+        db.record_vision_fallback_page(document.id, 2, "llava", Some(21.5)).await.unwrap();
+        db.record_vision_fallback_page(document.id, 1, "llava", Some(21.5)).await.unwrap();
+        // A later fallback run replaces the record of the page
+        db.record_vision_fallback_page(document.id, 1, "gpt-4o", Some(18.0)).await.unwrap();
+
+        let pages = db.get_vision_fallback_pages(document.id).await.unwrap();
+        let pages: Vec<_> = pages.iter().map(|page| (page.page_number, page.model.as_str(), page.ocr_confidence)).collect();
+        assert_eq!(pages, [(1, "gpt-4o", Some(18.0)), (2, "llava", Some(21.5))]);
+    }
+}
@@ -4,13 +4,14 @@ pub mod common; // Common utilities and shared functions
 pub mod config;
 pub mod service; 
 pub mod smart_sync;
+pub mod write_back;
 pub mod progress_shim; // Backward compatibility shim for simplified progress tracking
 
 // Re-export main types for convenience
 pub use common::build_user_agent;
 pub use config::{WebDAVConfig, RetryConfig, ConcurrencyConfig};
 pub use service::{
-    WebDAVService, WebDAVDiscoveryResult, WebDAVDownloadResult, ServerCapabilities, HealthStatus, test_webdav_connection,
+    WebDAVService, WebDAVDiscoveryResult, WebDAVDownloadResult, RemoteWriteOutcome, ServerCapabilities, HealthStatus, test_webdav_connection,
     ValidationReport, ValidationIssue, ValidationIssueType, ValidationSeverity, 
     ValidationRecommendation, ValidationAction, ValidationSummary
 };
@@ -11,18 +11,20 @@ use serde::{Deserialize, Serialize};
 use rand::Rng;
 
 use crate::models::{
-    FileIngestionInfo,
+    FileIngestionInfo, OutboundScope,
 };
 use crate::models::source::{
     WebDAVConnectionResult, WebDAVCrawlEstimate, WebDAVTestConnection,
 };
 use crate::models::source_error::{ErrorSourceType, ErrorContext};
+use crate::services::outbound_limits;
 use crate::services::source_error_tracker::SourceErrorTracker;
-use crate::webdav_xml_parser::{parse_propfind_response, parse_propfind_response_with_directories};
+use crate::webdav_xml_parser::{compare_etags, parse_propfind_response, parse_propfind_response_with_directories};
 use crate::mime_detection::{detect_mime_from_content, MimeDetectionResult};
 
 use super::{config::{WebDAVConfig, RetryConfig, ConcurrencyConfig}, SyncProgress};
 use super::common::build_user_agent;
+use crate::monitoring::telemetry::inject_trace_context;
 
 /// Results from WebDAV discovery including both files and directories
 #[derive(Debug, Clone)]
@@ -31,6 +33,16 @@ pub struct WebDAVDiscoveryResult {
     pub directories: Vec<FileIngestionInfo>,
 }
 
+/// Result of deleting or moving a file on the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteWriteOutcome {
+    Applied,
+    /// The file is no longer on the server
+    Missing,
+    /// The file changed on the server or the destination is taken; nothing was written
+    Conflict(String),
+}
+
 /// Result of downloading a file with MIME type detection
 #[derive(Debug, Clone)]
 pub struct WebDAVDownloadResult {
@@ -289,8 +301,7 @@ impl WebDAVService {
         let webdav_url = temp_config.webdav_url();
         debug!("📍 Testing WebDAV URL: {}", webdav_url);
         
-        let response = self.client
-            .request(Method::OPTIONS, &webdav_url)
+        let response = inject_trace_context(self.client.request(Method::OPTIONS, &webdav_url))
             .basic_auth(&self.config.username, Some(&self.config.password))
             .send()
             .await
@@ -432,8 +443,7 @@ impl WebDAVService {
         };
         let webdav_url = temp_config.webdav_url();
         
-        let response = self.client
-            .request(Method::OPTIONS, &webdav_url)
+        let response = inject_trace_context(self.client.request(Method::OPTIONS, &webdav_url))
             .basic_auth(&self.config.username, Some(&self.config.password))
             .send()
             .await?;
@@ -503,8 +513,7 @@ impl WebDAVService {
         let capabilities_url = format!("{}/ocs/v1.php/cloud/capabilities", 
             effective_server_url.trim_end_matches('/'));
 
-        let response = self.client
-            .get(&capabilities_url)
+        let response = inject_trace_context(self.client.get(&capabilities_url))
             .basic_auth(&self.config.username, Some(&self.config.password))
             .header("OCS-APIRequest", "true")
             .send()
@@ -686,8 +695,7 @@ impl WebDAVService {
         }
 
         loop {
-            let mut request = self.client
-                .request(method.clone(), url)
+            let mut request = inject_trace_context(self.client.request(method.clone(), url))
                 .basic_auth(&self.config.username, Some(&self.config.password))
                 .header("User-Agent", &user_agent);
 
@@ -702,7 +710,10 @@ impl WebDAVService {
             }
 
             debug!("📤 Sending HTTP {} request to: {}", method, url);
-            match request.send().await {
+            let permit = outbound_limits::acquire(OutboundScope::Webdav).await;
+            let sent = request.send().await;
+            drop(permit);
+            match sent {
                 Ok(response) => {
                     let status = response.status();
                     debug!("📥 HTTP Response: {} {}", status.as_u16(), status.canonical_reason().unwrap_or(""));
@@ -2227,6 +2238,127 @@ impl WebDAVService {
         }
     }
 
+    // ============================================================================
+    // Write-back Operations (bidirectional sources)
+    // ============================================================================
+
+    /// Current ETag of a file, or None when it no longer exists
+    pub async fn get_file_etag(&self, file_path: &str) -> Result<Option<String>> {
+        let url = self.get_url_for_path(&self.convert_to_relative_path(file_path));
+        let propfind_body = r#"<?xml version="1.0" encoding="utf-8"?>
+            <D:propfind xmlns:D="DAV:">
+                <D:prop>
+                    <D:getetag/>
+                    <D:resourcetype/>
+                </D:prop>
+            </D:propfind>"#;
+
+        let response = self.send_write_request(
+            Method::from_bytes(b"PROPFIND")?,
+            &url,
+            vec![("Depth", "0".to_string()), ("Content-Type", "application/xml".to_string())],
+            Some(propfind_body.to_string()),
+        ).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(anyhow!("Failed to get ETag of '{}': HTTP {}", file_path, status));
+        }
+
+        let files = parse_propfind_response(&response.text().await?)?;
+        Ok(files.into_iter().next().map(|f| f.etag))
+    }
+
+    /// Deletes a file unless it changed on the server since it had `expected_etag`
+    pub async fn delete_file(&self, file_path: &str, expected_etag: Option<&str>) -> Result<RemoteWriteOutcome> {
+        if let Some(outcome) = self.check_remote_etag(file_path, expected_etag).await? {
+            return Ok(outcome);
+        }
+
+        let url = self.get_url_for_path(&self.convert_to_relative_path(file_path));
+        let response = self.send_write_request(Method::DELETE, &url, Vec::new(), None).await?;
+        match response.status() {
+            status if status.is_success() => {
+                info!("🗑️ Deleted '{}' on WebDAV server", file_path);
+                Ok(RemoteWriteOutcome::Applied)
+            }
+            reqwest::StatusCode::NOT_FOUND => Ok(RemoteWriteOutcome::Missing),
+            status => Err(anyhow!("Failed to delete '{}': HTTP {}", file_path, status)),
+        }
+    }
+
+    /// Moves a file to `destination_path` unless it changed on the server since it had
+    /// `expected_etag`. An existing file at the destination is never overwritten.
+    pub async fn move_file(&self, file_path: &str, destination_path: &str, expected_etag: Option<&str>) -> Result<RemoteWriteOutcome> {
+        if let Some(outcome) = self.check_remote_etag(file_path, expected_etag).await? {
+            return Ok(outcome);
+        }
+
+        let url = self.get_url_for_path(&self.convert_to_relative_path(file_path));
+        let destination = reqwest::Url::parse(&self.get_url_for_path(&self.convert_to_relative_path(destination_path)))
+            .map_err(|e| anyhow!("Invalid destination '{}': {}", destination_path, e))?;
+        let response = self.send_write_request(
+            Method::from_bytes(b"MOVE")?,
+            &url,
+            vec![("Destination", destination.to_string()), ("Overwrite", "F".to_string())],
+            None,
+        ).await?;
+
+        match response.status() {
+            status if status.is_success() => {
+                info!("📝 Moved '{}' to '{}' on WebDAV server", file_path, destination_path);
+                Ok(RemoteWriteOutcome::Applied)
+            }
+            reqwest::StatusCode::NOT_FOUND => Ok(RemoteWriteOutcome::Missing),
+            reqwest::StatusCode::PRECONDITION_FAILED => Ok(RemoteWriteOutcome::Conflict(
+                format!("'{}' already exists on the server", destination_path)
+            )),
+            status => Err(anyhow!("Failed to move '{}' to '{}': HTTP {}", file_path, destination_path, status)),
+        }
+    }
+
+    /// Compares the server's ETag with the one the file had when it was synced
+    async fn check_remote_etag(&self, file_path: &str, expected_etag: Option<&str>) -> Result<Option<RemoteWriteOutcome>> {
+        let Some(current_etag) = self.get_file_etag(file_path).await? else {
+            return Ok(Some(RemoteWriteOutcome::Missing));
+        };
+        match expected_etag {
+            Some(expected) if compare_etags(expected, &current_etag) => Ok(None),
+            Some(expected) => Ok(Some(RemoteWriteOutcome::Conflict(format!(
+                "'{}' changed on the server (ETag {} instead of {})", file_path, current_etag, expected
+            )))),
+            None => Ok(Some(RemoteWriteOutcome::Conflict(format!(
+                "no ETag was recorded for '{}' when it was synced", file_path
+            )))),
+        }
+    }
+
+    /// Sends a single request without retries, so the caller sees the exact status.
+    /// Write operations are not idempotent across a MOVE that already succeeded.
+    async fn send_write_request(
+        &self,
+        method: Method,
+        url: &str,
+        headers: Vec<(&str, String)>,
+        body: Option<String>,
+    ) -> Result<reqwest::Response> {
+        debug!("📤 Sending HTTP {} request to: {}", method, url);
+        let mut request = inject_trace_context(self.client.request(method, url))
+            .basic_auth(&self.config.username, Some(&self.config.password))
+            .header("User-Agent", build_user_agent());
+        for (key, value) in headers {
+            request = request.header(key, value);
+        }
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+        let _permit = outbound_limits::acquire(OutboundScope::Webdav).await;
+        Ok(request.send().await?)
+    }
+
     // ============================================================================
     // Server Capabilities and Health Checks
     // ============================================================================
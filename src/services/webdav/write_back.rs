@@ -0,0 +1,168 @@
+use anyhow::Result;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{Document, FileIngestionInfo, SourceType, WebDAVSourceConfig, WebDAVSyncDirection};
+
+use super::{RemoteWriteOutcome, WebDAVService};
+
+pub const OPERATION_DELETE: &str = "delete";
+pub const OPERATION_RENAME: &str = "rename";
+
+/// A change that keeps failing is given up after this many attempts
+const MAX_ATTEMPTS: i32 = 5;
+
+#[derive(Debug, Default, Clone)]
+pub struct WriteBackSummary {
+    pub applied: usize,
+    pub conflicts: usize,
+    pub failed: usize,
+}
+
+/// Queue deleting the document's file on its WebDAV source. Does nothing unless the
+/// document came from a bidirectional WebDAV source. Returns whether a change was queued.
+pub async fn queue_document_deletion(db: &Database, document: &Document) -> Result<bool> {
+    let Some((source_id, remote_path)) = bidirectional_source(db, document).await? else {
+        return Ok(false);
+    };
+
+    db.queue_webdav_outgoing_change(source_id, document.id, OPERATION_DELETE, &remote_path, None, synced_etag(document))
+        .await?;
+    info!("Queued deletion of '{}' on WebDAV source {}", remote_path, source_id);
+    Ok(true)
+}
+
+/// Queue renaming the document's file on its WebDAV source to `new_filename` in the
+/// same folder. Returns the new remote path when a change was queued.
+pub async fn queue_document_rename(db: &Database, document: &Document, new_filename: &str) -> Result<Option<String>> {
+    let Some((source_id, remote_path)) = bidirectional_source(db, document).await? else {
+        return Ok(None);
+    };
+
+    let new_remote_path = renamed_path(&remote_path, new_filename);
+    if new_remote_path == remote_path {
+        return Ok(None);
+    }
+
+    db.queue_webdav_outgoing_change(
+        source_id,
+        document.id,
+        OPERATION_RENAME,
+        &remote_path,
+        Some(&new_remote_path),
+        synced_etag(document),
+    )
+    .await?;
+    info!("Queued rename of '{}' to '{}' on WebDAV source {}", remote_path, new_remote_path, source_id);
+    Ok(Some(new_remote_path))
+}
+
+/// Apply a source's pending changes in the order they were made. Changes whose file
+/// changed on the server are marked as conflicts and left for the pull to pick up.
+pub async fn apply_pending_changes(db: &Database, source_id: Uuid, service: &WebDAVService) -> Result<WriteBackSummary> {
+    let mut summary = WriteBackSummary::default();
+
+    for change in db.get_pending_webdav_outgoing_changes(source_id).await? {
+        let outcome = match (change.operation.as_str(), change.new_remote_path.as_deref()) {
+            (OPERATION_RENAME, Some(new_remote_path)) => {
+                service.move_file(&change.remote_path, new_remote_path, change.expected_etag.as_deref()).await
+            }
+            _ => service.delete_file(&change.remote_path, change.expected_etag.as_deref()).await,
+        };
+
+        let (status, error) = match outcome {
+            Ok(RemoteWriteOutcome::Applied) => {
+                summary.applied += 1;
+                ("applied", None)
+            }
+            Ok(RemoteWriteOutcome::Missing) if change.operation == OPERATION_DELETE => {
+                summary.applied += 1;
+                ("applied", Some("file was already gone from the server".to_string()))
+            }
+            Ok(RemoteWriteOutcome::Missing) => {
+                summary.conflicts += 1;
+                ("conflict", Some(format!("'{}' is no longer on the server", change.remote_path)))
+            }
+            Ok(RemoteWriteOutcome::Conflict(detail)) => {
+                warn!("WebDAV {} of '{}' not applied: {}", change.operation, change.remote_path, detail);
+                summary.conflicts += 1;
+                ("conflict", Some(detail))
+            }
+            Err(e) => {
+                warn!("WebDAV {} of '{}' failed (attempt {}): {}", change.operation, change.remote_path, change.attempts + 1, e);
+                if change.attempts + 1 >= MAX_ATTEMPTS {
+                    summary.failed += 1;
+                    ("failed", Some(e.to_string()))
+                } else {
+                    ("pending", Some(e.to_string()))
+                }
+            }
+        };
+
+        db.update_webdav_outgoing_change(change.id, status, error.as_deref()).await?;
+    }
+
+    Ok(summary)
+}
+
+/// Keep the file's ETag in the source metadata of the document ingested from it,
+/// so a later write-back can tell whether the file changed on the server
+pub fn with_etag_metadata(mut file: FileIngestionInfo) -> FileIngestionInfo {
+    let mut metadata = match file.metadata.take() {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    metadata.insert("etag".to_string(), serde_json::Value::String(file.etag.clone()));
+    file.metadata = Some(serde_json::Value::Object(metadata));
+    file
+}
+
+/// `remote_path` with its last segment replaced by `new_filename`
+pub fn renamed_path(remote_path: &str, new_filename: &str) -> String {
+    match remote_path.rsplit_once('/') {
+        Some((folder, _)) => format!("{}/{}", folder, new_filename),
+        None => new_filename.to_string(),
+    }
+}
+
+/// Source id and remote path of a document from a bidirectional WebDAV source
+async fn bidirectional_source(db: &Database, document: &Document) -> Result<Option<(Uuid, String)>> {
+    let (Some(source_id), Some(remote_path)) = (document.source_id, document.source_path.as_ref()) else {
+        return Ok(None);
+    };
+    let Some(source) = db.get_source(document.user_id, source_id).await? else {
+        return Ok(None);
+    };
+    if source.source_type != SourceType::WebDAV {
+        return Ok(None);
+    }
+
+    let config: WebDAVSourceConfig = serde_json::from_value(source.config)?;
+    if config.sync_direction != WebDAVSyncDirection::Bidirectional {
+        return Ok(None);
+    }
+
+    Ok(Some((source_id, remote_path.clone())))
+}
+
+/// ETag recorded in the document's source metadata when it was synced
+fn synced_etag(document: &Document) -> Option<&str> {
+    document
+        .source_metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("etag"))
+        .and_then(|etag| etag.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renamed_path() {
+        assert_eq!(renamed_path("/Documents/Scans/a.pdf", "invoice.pdf"), "/Documents/Scans/invoice.pdf");
+        assert_eq!(renamed_path("/a.pdf", "b.pdf"), "/b.pdf");
+        assert_eq!(renamed_path("a.pdf", "b.pdf"), "b.pdf");
+    }
+}
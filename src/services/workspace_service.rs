@@ -0,0 +1,84 @@
+use anyhow::Result;
+use std::future::Future;
+use uuid::Uuid;
+
+use crate::db::{workspaces::WorkspaceScoped, Database};
+use crate::models::{User, UserRole, WorkspaceRole};
+
+tokio::task_local! {
+    static WORKSPACE: Option<Uuid>;
+}
+
+/// Header naming the workspace a request works in
+pub const WORKSPACE_HEADER: &str = "x-workspace-id";
+
+/// Longest workspace name and slug
+pub const MAX_NAME_LENGTH: usize = 100;
+
+/// Whether workspaces are in use; off unless `MULTI_TENANT_MODE` is true. Without it
+/// everything stays personal to its owner and the workspace header is ignored.
+pub fn enabled() -> bool {
+    std::env::var("MULTI_TENANT_MODE")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false)
+}
+
+/// Lowercase letters, digits and single dashes; None when nothing of the input is left
+pub fn slugify(input: &str) -> Option<String> {
+    let mut slug = String::new();
+    for c in input.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() || slug.len() > MAX_NAME_LENGTH {
+        None
+    } else {
+        Some(slug.to_string())
+    }
+}
+
+/// Whether the user holds at least `role` in the workspace; instance admins hold every role
+pub async fn has_role(db: &Database, user: &User, workspace_id: Uuid, role: WorkspaceRole) -> Result<bool> {
+    if user.role == UserRole::Admin {
+        return Ok(true);
+    }
+    Ok(db.get_workspace_role(workspace_id, user.id).await? >= Some(role))
+}
+
+/// Places a row created during a request in the request's workspace, if it has one
+pub async fn place_in_workspace(db: &Database, scoped: WorkspaceScoped, id: Uuid, workspace_id: Option<Uuid>) {
+    let Some(workspace_id) = workspace_id else {
+        return;
+    };
+    if let Err(e) = db.assign_to_workspace(scoped, id, workspace_id).await {
+        tracing::error!("Failed to place {:?} {} in workspace {}: {}", scoped, id, workspace_id, e);
+    }
+}
+
+/// Run work done in a workspace, e.g. an upload that is placed in it once stored, so
+/// what it stores is sealed with the workspace's key
+pub async fn within<F: Future>(workspace_id: Option<Uuid>, work: F) -> F::Output {
+    WORKSPACE.scope(workspace_id, work).await
+}
+
+/// The workspace the running work is done in, if any
+pub fn current() -> Option<Uuid> {
+    WORKSPACE.try_with(|workspace_id| *workspace_id).ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Acme Corp.").as_deref(), Some("acme-corp"));
+        assert_eq!(slugify("  Müller & Söhne ").as_deref(), Some("m-ller-s-hne"));
+        assert_eq!(slugify("--"), None);
+        assert_eq!(slugify(&"a".repeat(MAX_NAME_LENGTH + 1)), None);
+    }
+}
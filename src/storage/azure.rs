@@ -0,0 +1,413 @@
+//! Azure Blob Storage backend implementation
+//!
+//! Talks to the Blob service REST API directly and signs requests with the
+//! storage account's shared key, so no Azure SDK is needed.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use base64ct::{Base64, Encoding};
+use chrono::Utc;
+use futures::TryStreamExt;
+use hmac::{Hmac, Mac};
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE};
+use reqwest::{Method, StatusCode, Url};
+use sha2::Sha256;
+use std::path::Path;
+use std::time::Duration;
+use tokio_util::io::ReaderStream;
+use tracing::info;
+use uuid::Uuid;
+
+use super::{FileStream, StorageBackend, BLOBS_DIR, LIBRARY_DIR};
+use crate::utils::security::validate_filename;
+
+/// Blob service REST API version the requests are signed for
+const API_VERSION: &str = "2021-08-06";
+
+/// Largest blob a single Put Blob request accepts
+const MAX_PUT_BLOB_SIZE: u64 = 5000 * 1024 * 1024;
+
+/// Prefix of the storage paths of files kept in Azure Blob Storage
+pub const AZURE_PATH_PREFIX: &str = "azure://";
+
+/// Azure Blob Storage account and container that documents are stored in
+#[derive(Debug, Clone)]
+pub struct AzureBlobConfig {
+    pub account_name: String,
+    /// Base64 account access key
+    pub account_key: String,
+    pub container: String,
+    /// Blob service endpoint, e.g. for Azurite; defaults to the public cloud endpoint
+    pub endpoint_url: Option<String>,
+}
+
+impl AzureBlobConfig {
+    /// None when `AZURE_STORAGE_ACCOUNT` is not set
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(account_name) = env_value("AZURE_STORAGE_ACCOUNT") else {
+            return Ok(None);
+        };
+        let account_key = env_value("AZURE_STORAGE_KEY")
+            .ok_or_else(|| anyhow!("AZURE_STORAGE_KEY must be set when AZURE_STORAGE_ACCOUNT is"))?;
+        Base64::decode_vec(&account_key).map_err(|_| anyhow!("AZURE_STORAGE_KEY is not valid base64"))?;
+
+        Ok(Some(Self {
+            account_name,
+            account_key,
+            container: env_value("AZURE_STORAGE_CONTAINER").unwrap_or_else(|| "readur".to_string()),
+            endpoint_url: env_value("AZURE_STORAGE_ENDPOINT"),
+        }))
+    }
+
+    fn endpoint(&self) -> String {
+        match &self.endpoint_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => format!("https://{}.blob.core.windows.net", self.account_name),
+        }
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Request body with the headers that describe it
+struct BlobBody {
+    body: reqwest::Body,
+    length: u64,
+    content_type: Option<String>,
+}
+
+/// Storage backend that keeps files as block blobs in one container
+pub struct AzureBlobBackend {
+    client: reqwest::Client,
+    config: AzureBlobConfig,
+    key: Vec<u8>,
+}
+
+impl AzureBlobBackend {
+    pub fn new(config: AzureBlobConfig) -> Result<Self> {
+        let key = Base64::decode_vec(config.account_key.trim())
+            .map_err(|_| anyhow!("Azure storage account key is not valid base64"))?;
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(30))
+            .build()?;
+
+        Ok(Self { client, config, key })
+    }
+
+    fn document_blob(user_id: Uuid, document_id: Uuid, filename: &str) -> Result<String> {
+        let sanitized_filename = validate_filename(filename)?;
+        let extension = Path::new(&sanitized_filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+
+        Ok(if extension.is_empty() {
+            format!("documents/{}/{}", user_id, document_id)
+        } else {
+            format!("documents/{}/{}.{}", user_id, document_id, extension)
+        })
+    }
+
+    fn thumbnail_blob(user_id: Uuid, document_id: Uuid) -> String {
+        format!("thumbnails/{}/{}_thumb.jpg", user_id, document_id)
+    }
+
+    fn processed_image_blob(user_id: Uuid, document_id: Uuid) -> String {
+        format!("processed_images/{}/{}_processed.png", user_id, document_id)
+    }
+
+    fn blob_name(path: &str) -> &str {
+        path.strip_prefix(AZURE_PATH_PREFIX).unwrap_or(path)
+    }
+
+    fn container_url(&self) -> Result<Url> {
+        Ok(Url::parse(&format!("{}/{}?restype=container", self.config.endpoint(), self.config.container))?)
+    }
+
+    fn blob_url(&self, blob: &str) -> Result<Url> {
+        let mut url = Url::parse(&self.config.endpoint())?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("Azure storage endpoint cannot be used as a base URL"))?
+            .pop_if_empty()
+            .push(&self.config.container)
+            .extend(blob.split('/'));
+        Ok(url)
+    }
+
+    async fn send(&self, method: Method, url: Url, extra_headers: &[(&str, &str)], body: Option<BlobBody>) -> Result<reqwest::Response> {
+        let mut ms_headers: Vec<(String, String)> = extra_headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        ms_headers.push(("x-ms-date".to_string(), Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string()));
+        ms_headers.push(("x-ms-version".to_string(), API_VERSION.to_string()));
+        ms_headers.sort();
+
+        let (length, content_type) = match &body {
+            Some(body) => (body.length, body.content_type.as_deref().unwrap_or("")),
+            None => (0, ""),
+        };
+        let to_sign = string_to_sign(method.as_str(), length, content_type, &ms_headers, &self.config.account_name, &url);
+        let authorization = format!("SharedKey {}:{}", self.config.account_name, sign(&self.key, &to_sign));
+
+        let mut request = self.client.request(method, url).header(AUTHORIZATION, authorization);
+        for (name, value) in &ms_headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(body) = body {
+            if let Some(content_type) = body.content_type {
+                request = request.header(CONTENT_TYPE, content_type);
+            }
+            request = request.header(CONTENT_LENGTH, body.length.to_string()).body(body.body);
+        }
+
+        Ok(request.send().await?)
+    }
+
+    async fn put_blob(&self, blob: &str, body: reqwest::Body, length: u64, content_type: &str) -> Result<()> {
+        if length > MAX_PUT_BLOB_SIZE {
+            return Err(anyhow!("File too large for Azure Blob Storage (max 5000 MiB)"));
+        }
+
+        let response = self
+            .send(
+                Method::PUT,
+                self.blob_url(blob)?,
+                &[("x-ms-blob-type", "BlockBlob")],
+                Some(BlobBody { body, length, content_type: Some(content_type.to_string()) }),
+            )
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to upload blob {}: HTTP {}", blob, response.status()));
+        }
+
+        Ok(())
+    }
+
+    async fn get_blob(&self, blob: &str, extra_headers: &[(&str, &str)]) -> Result<reqwest::Response> {
+        let response = self.send(Method::GET, self.blob_url(blob)?, extra_headers, None).await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to retrieve blob {}: HTTP {}", blob, response.status()));
+        }
+
+        Ok(response)
+    }
+
+    async fn delete_blob(&self, blob: &str) -> Result<()> {
+        let response = self.send(Method::DELETE, self.blob_url(blob)?, &[], None).await?;
+        match response.status() {
+            status if status.is_success() || status == StatusCode::NOT_FOUND => Ok(()),
+            status => Err(anyhow!("Failed to delete blob {}: HTTP {}", blob, status)),
+        }
+    }
+}
+
+/// The string that Shared Key authorization signs for a Blob service request
+fn string_to_sign(
+    method: &str,
+    content_length: u64,
+    content_type: &str,
+    ms_headers: &[(String, String)],
+    account_name: &str,
+    url: &Url,
+) -> String {
+    // Content-Length is signed as an empty string when there is no body
+    let content_length = if content_length == 0 { String::new() } else { content_length.to_string() };
+    let mut to_sign = format!("{}\n\n\n{}\n\n{}\n\n\n\n\n\n\n", method, content_length, content_type);
+
+    for (name, value) in ms_headers {
+        to_sign.push_str(&format!("{}:{}\n", name.to_lowercase(), value.trim()));
+    }
+
+    to_sign.push_str(&format!("/{}{}", account_name, url.path()));
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| (name.to_lowercase(), value.into_owned()))
+        .collect();
+    query.sort();
+    for (name, value) in query {
+        to_sign.push_str(&format!("\n{}:{}", name, value));
+    }
+
+    to_sign
+}
+
+fn sign(key: &[u8], to_sign: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(to_sign.as_bytes());
+    Base64::encode_string(&mac.finalize().into_bytes())
+}
+
+#[async_trait]
+impl StorageBackend for AzureBlobBackend {
+    fn as_any(&self) -> Option<&dyn std::any::Any> {
+        Some(self)
+    }
+
+    async fn store_document(&self, user_id: Uuid, document_id: Uuid, filename: &str, data: &[u8]) -> Result<String> {
+        let blob = Self::document_blob(user_id, document_id, filename)?;
+        let content_type = mime_guess::from_path(filename).first_or_octet_stream();
+        self.put_blob(&blob, reqwest::Body::from(data.to_vec()), data.len() as u64, content_type.as_ref()).await?;
+
+        info!("Stored document in Azure Blob Storage: {}", blob);
+        Ok(format!("{}{}", AZURE_PATH_PREFIX, blob))
+    }
+
+    async fn store_document_from_path(&self, user_id: Uuid, document_id: Uuid, filename: &str, source: &Path) -> Result<String> {
+        let blob = Self::document_blob(user_id, document_id, filename)?;
+        let content_type = mime_guess::from_path(filename).first_or_octet_stream();
+        let file = tokio::fs::File::open(source).await?;
+        let length = file.metadata().await?.len();
+        self.put_blob(&blob, reqwest::Body::wrap_stream(ReaderStream::new(file)), length, content_type.as_ref()).await?;
+
+        info!("Stored document in Azure Blob Storage: {} ({} bytes)", blob, length);
+        Ok(format!("{}{}", AZURE_PATH_PREFIX, blob))
+    }
+
+    async fn store_thumbnail(&self, user_id: Uuid, document_id: Uuid, data: &[u8]) -> Result<String> {
+        let blob = Self::thumbnail_blob(user_id, document_id);
+        self.put_blob(&blob, reqwest::Body::from(data.to_vec()), data.len() as u64, "image/jpeg").await?;
+        Ok(format!("{}{}", AZURE_PATH_PREFIX, blob))
+    }
+
+    async fn store_processed_image(&self, user_id: Uuid, document_id: Uuid, data: &[u8]) -> Result<String> {
+        let blob = Self::processed_image_blob(user_id, document_id);
+        self.put_blob(&blob, reqwest::Body::from(data.to_vec()), data.len() as u64, "image/png").await?;
+        Ok(format!("{}{}", AZURE_PATH_PREFIX, blob))
+    }
+
+    async fn retrieve_file(&self, path: &str) -> Result<Vec<u8>> {
+        let response = self.get_blob(Self::blob_name(path), &[]).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn retrieve_stream(&self, path: &str) -> Result<FileStream> {
+        let response = self.get_blob(Self::blob_name(path), &[]).await?;
+        Ok(FileStream {
+            size: response.content_length(),
+            chunks: Box::pin(response.bytes_stream().map_err(std::io::Error::other)),
+        })
+    }
+
+    async fn store_content_blob(&self, key: &str, data: &[u8]) -> Result<String> {
+        let blob = format!("{}/{}", BLOBS_DIR, key);
+        let content_type = mime_guess::from_path(key).first_or_octet_stream();
+        self.put_blob(&blob, reqwest::Body::from(data.to_vec()), data.len() as u64, content_type.as_ref()).await?;
+        Ok(format!("{}{}", AZURE_PATH_PREFIX, blob))
+    }
+
+    async fn delete_content_blob(&self, path: &str) -> Result<()> {
+        self.delete_blob(Self::blob_name(path)).await
+    }
+
+    async fn store_library_file(&self, relative_path: &str, data: &[u8]) -> Result<String> {
+        let blob = format!("{}/{}", LIBRARY_DIR, relative_path);
+        let content_type = mime_guess::from_path(relative_path).first_or_octet_stream();
+        self.put_blob(&blob, reqwest::Body::from(data.to_vec()), data.len() as u64, content_type.as_ref()).await?;
+        Ok(format!("{}{}", AZURE_PATH_PREFIX, blob))
+    }
+
+    async fn delete_stored_file(&self, path: &str) -> Result<()> {
+        self.delete_blob(Self::blob_name(path)).await
+    }
+
+    async fn retrieve_range_stream(&self, path: &str, start: u64, end: u64) -> Result<FileStream> {
+        let range = format!("bytes={}-{}", start, end);
+        let response = self.get_blob(Self::blob_name(path), &[("x-ms-range", &range)]).await?;
+        Ok(FileStream {
+            size: response.content_length(),
+            chunks: Box::pin(response.bytes_stream().map_err(std::io::Error::other)),
+        })
+    }
+
+    async fn delete_document_files(&self, user_id: Uuid, document_id: Uuid, filename: &str) -> Result<()> {
+        let blobs = [
+            Self::document_blob(user_id, document_id, filename)?,
+            Self::thumbnail_blob(user_id, document_id),
+            Self::processed_image_blob(user_id, document_id),
+        ];
+
+        let mut errors = Vec::new();
+        for blob in &blobs {
+            if let Err(e) = self.delete_blob(blob).await {
+                errors.push(e.to_string());
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(anyhow!("Failed to delete some files: {}", errors.join("; ")));
+        }
+
+        info!("Deleted all Azure blobs for document {}", document_id);
+        Ok(())
+    }
+
+    async fn file_exists(&self, path: &str) -> Result<bool> {
+        let blob = Self::blob_name(path);
+        let response = self.send(Method::HEAD, self.blob_url(blob)?, &[], None).await?;
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            StatusCode::NOT_FOUND => Ok(false),
+            status => Err(anyhow!("Failed to check blob {}: HTTP {}", blob, status)),
+        }
+    }
+
+    fn storage_type(&self) -> &'static str {
+        "azure"
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        let response = self
+            .send(
+                Method::PUT,
+                self.container_url()?,
+                &[],
+                Some(BlobBody { body: reqwest::Body::from(Vec::new()), length: 0, content_type: None }),
+            )
+            .await?;
+
+        // 409 means the container already exists
+        if !response.status().is_success() && response.status() != StatusCode::CONFLICT {
+            return Err(anyhow!(
+                "Failed to access Azure container {}: HTTP {}",
+                self.config.container,
+                response.status()
+            ));
+        }
+
+        info!("Azure Blob Storage backend initialized (container {})", self.config.container);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_key_signature() {
+        let url = Url::parse("https://acct.blob.core.windows.net/readur/documents/a/b.pdf?comp=metadata&Timeout=30").unwrap();
+        let ms_headers = vec![
+            ("x-ms-blob-type".to_string(), "BlockBlob".to_string()),
+            ("x-ms-date".to_string(), "Fri, 26 Jun 2015 23:39:12 GMT".to_string()),
+            ("x-ms-version".to_string(), API_VERSION.to_string()),
+        ];
+
+        let to_sign = string_to_sign("PUT", 11, "application/pdf", &ms_headers, "acct", &url);
+        assert_eq!(
+            to_sign,
+            "PUT\n\n\n11\n\napplication/pdf\n\n\n\n\n\n\n\
+             x-ms-blob-type:BlockBlob\nx-ms-date:Fri, 26 Jun 2015 23:39:12 GMT\nx-ms-version:2021-08-06\n\
+             /acct/readur/documents/a/b.pdf\ncomp:metadata\ntimeout:30"
+        );
+        assert_eq!(sign(b"secret", &to_sign), "5I6EvLrChCKciF9KpslZXKF80xXsY1Nx62HtCyV7Nz0=");
+
+        let to_sign = string_to_sign("GET", 0, "", &ms_headers[1..], "acct", &url);
+        assert!(to_sign.starts_with("GET\n\n\n\n\n\n"));
+    }
+}
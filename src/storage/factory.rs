@@ -4,6 +4,7 @@ use anyhow::Result;
 use std::sync::Arc;
 
 use super::{StorageBackend, StorageConfig};
+use super::azure::{AzureBlobBackend, AzureBlobConfig};
 use super::local::LocalStorageBackend;
 
 #[cfg(feature = "s3")]
@@ -23,11 +24,48 @@ pub async fn create_storage_backend(config: StorageConfig) -> Result<Arc<dyn Sto
             backend.initialize().await?;
             Ok(Arc::new(backend))
         }
+        StorageConfig::Azure { azure_config } => {
+            let backend = AzureBlobBackend::new(azure_config)?;
+            backend.initialize().await?;
+            Ok(Arc::new(backend))
+        }
+    }
+}
+
+/// Backends other than the default one that files may still be read from, e.g.
+/// while documents are being migrated between backends. Only backends whose
+/// configuration is present in the environment are created.
+pub async fn create_read_backends(config: &crate::config::Config, default_type: &str) -> Result<Vec<Arc<dyn StorageBackend>>> {
+    let mut backends: Vec<Arc<dyn StorageBackend>> = Vec::new();
+
+    if default_type != "local" {
+        backends.push(Arc::new(LocalStorageBackend::new(config.upload_path.clone())));
+    }
+
+    #[cfg(feature = "s3")]
+    {
+        if let Some(s3_config) = config.s3_config.as_ref().filter(|_| default_type != "s3") {
+            backends.push(Arc::new(S3Service::new(s3_config.clone()).await?));
+        }
+    }
+
+    if default_type != "azure" {
+        if let Some(azure_config) = AzureBlobConfig::from_env()? {
+            backends.push(Arc::new(AzureBlobBackend::new(azure_config)?));
+        }
     }
+
+    Ok(backends)
 }
 
 /// Create storage configuration from environment variables
 pub fn storage_config_from_env(config: &crate::config::Config) -> Result<StorageConfig> {
+    if std::env::var("STORAGE_TYPE").is_ok_and(|value| value.trim().eq_ignore_ascii_case("azure")) {
+        let azure_config = AzureBlobConfig::from_env()?
+            .ok_or_else(|| anyhow::anyhow!("STORAGE_TYPE=azure requires AZURE_STORAGE_ACCOUNT and AZURE_STORAGE_KEY"))?;
+        return Ok(StorageConfig::Azure { azure_config });
+    }
+
     if config.s3_enabled {
         #[cfg(feature = "s3")]
         {
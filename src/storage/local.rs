@@ -6,11 +6,13 @@ use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::sync::RwLock;
 use tracing::{info, error, warn, debug};
+use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 
-use super::StorageBackend;
+use super::{FileStream, StorageBackend, BLOBS_DIR, LIBRARY_DIR};
 use crate::utils::security::{validate_filename, validate_and_sanitize_path, validate_path_within_base};
 
 /// Local filesystem storage backend
@@ -186,6 +188,37 @@ impl LocalStorageBackend {
         debug!("Invalidated cache entry for: {}", file_path);
     }
 
+    /// Path a document is stored at, with the documents directory created
+    async fn prepare_document_path(&self, document_id: Uuid, filename: &str) -> Result<PathBuf> {
+        // Validate and sanitize the filename
+        let sanitized_filename = validate_filename(filename)?;
+        
+        let extension = Path::new(&sanitized_filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+        
+        let document_filename = if extension.is_empty() {
+            document_id.to_string()
+        } else {
+            format!("{}.{}", document_id, extension)
+        };
+        
+        let documents_dir = self.get_documents_path();
+        let file_path = documents_dir.join(&document_filename);
+        
+        // Validate that the final path is within our base directory
+        validate_path_within_base(
+            &file_path.to_string_lossy(), 
+            &self.upload_path
+        )?;
+        
+        // Ensure the documents directory exists
+        fs::create_dir_all(&documents_dir).await?;
+
+        Ok(file_path)
+    }
+
     /// Save a file with generated UUID filename (legacy method)
     pub async fn save_file(&self, filename: &str, data: &[u8]) -> Result<String> {
         let file_id = Uuid::new_v4();
@@ -219,31 +252,7 @@ impl LocalStorageBackend {
 #[async_trait]
 impl StorageBackend for LocalStorageBackend {
     async fn store_document(&self, _user_id: Uuid, document_id: Uuid, filename: &str, data: &[u8]) -> Result<String> {
-        // Validate and sanitize the filename
-        let sanitized_filename = validate_filename(filename)?;
-        
-        let extension = Path::new(&sanitized_filename)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("");
-        
-        let document_filename = if extension.is_empty() {
-            document_id.to_string()
-        } else {
-            format!("{}.{}", document_id, extension)
-        };
-        
-        let documents_dir = self.get_documents_path();
-        let file_path = documents_dir.join(&document_filename);
-        
-        // Validate that the final path is within our base directory
-        validate_path_within_base(
-            &file_path.to_string_lossy(), 
-            &self.upload_path
-        )?;
-        
-        // Ensure the documents directory exists
-        fs::create_dir_all(&documents_dir).await?;
+        let file_path = self.prepare_document_path(document_id, filename).await?;
         
         // Validate data size (prevent extremely large files from causing issues)
         if data.len() > 1_000_000_000 { // 1GB limit
@@ -260,6 +269,22 @@ impl StorageBackend for LocalStorageBackend {
         Ok(path_str)
     }
 
+    async fn store_document_from_path(&self, _user_id: Uuid, document_id: Uuid, filename: &str, source: &Path) -> Result<String> {
+        let file_path = self.prepare_document_path(document_id, filename).await?;
+
+        if fs::metadata(source).await?.len() > 1_000_000_000 { // 1GB limit
+            return Err(anyhow::anyhow!("File too large for storage (max 1GB)"));
+        }
+
+        fs::copy(source, &file_path).await?;
+
+        let path_str = file_path.to_string_lossy().to_string();
+        self.invalidate_cache_entry(&path_str).await;
+
+        info!("Stored document locally: {}", file_path.display());
+        Ok(path_str)
+    }
+
     async fn store_thumbnail(&self, _user_id: Uuid, document_id: Uuid, data: &[u8]) -> Result<String> {
         let thumbnails_dir = self.get_thumbnails_path();
         fs::create_dir_all(&thumbnails_dir).await?;
@@ -336,6 +361,94 @@ impl StorageBackend for LocalStorageBackend {
         Ok(data)
     }
 
+    async fn retrieve_stream(&self, path: &str) -> Result<FileStream> {
+        let sanitized_path = validate_and_sanitize_path(path)?;
+        let resolved_path = self.resolve_file_path(&sanitized_path).await?;
+        validate_path_within_base(&resolved_path, &self.upload_path)?;
+
+        let file = fs::File::open(&resolved_path).await?;
+        let size = file.metadata().await?.len();
+        Ok(FileStream {
+            size: Some(size),
+            chunks: Box::pin(ReaderStream::new(file)),
+        })
+    }
+
+    async fn store_content_blob(&self, key: &str, data: &[u8]) -> Result<String> {
+        let file_path = Path::new(&self.upload_path).join(BLOBS_DIR).join(key);
+        validate_path_within_base(&file_path.to_string_lossy(), &self.upload_path)?;
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        if data.len() > 1_000_000_000 { // 1GB limit
+            return Err(anyhow::anyhow!("File too large for storage (max 1GB)"));
+        }
+        fs::write(&file_path, data).await?;
+
+        let path_str = file_path.to_string_lossy().to_string();
+        self.invalidate_cache_entry(&path_str).await;
+        info!("Stored blob locally: {}", file_path.display());
+        Ok(path_str)
+    }
+
+    async fn delete_content_blob(&self, path: &str) -> Result<()> {
+        let sanitized_path = validate_and_sanitize_path(path)?;
+        validate_path_within_base(&sanitized_path, &self.upload_path)?;
+        match fs::remove_file(&sanitized_path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.invalidate_cache_entry(path).await;
+        Ok(())
+    }
+
+    async fn store_library_file(&self, relative_path: &str, data: &[u8]) -> Result<String> {
+        let file_path = Path::new(&self.upload_path).join(LIBRARY_DIR).join(relative_path);
+        validate_path_within_base(&file_path.to_string_lossy(), &self.upload_path)?;
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        if data.len() > 1_000_000_000 { // 1GB limit
+            return Err(anyhow::anyhow!("File too large for storage (max 1GB)"));
+        }
+        fs::write(&file_path, data).await?;
+
+        let path_str = file_path.to_string_lossy().to_string();
+        self.invalidate_cache_entry(&path_str).await;
+        info!("Stored document in library layout: {}", file_path.display());
+        Ok(path_str)
+    }
+
+    async fn delete_stored_file(&self, path: &str) -> Result<()> {
+        let sanitized_path = validate_and_sanitize_path(path)?;
+        let resolved_path = self.resolve_file_path(&sanitized_path).await.unwrap_or(sanitized_path);
+        validate_path_within_base(&resolved_path, &self.upload_path)?;
+        match fs::remove_file(&resolved_path).await {
+            Ok(()) => info!("Deleted file: {}", resolved_path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.invalidate_cache_entry(path).await;
+        Ok(())
+    }
+
+    async fn retrieve_range_stream(&self, path: &str, start: u64, end: u64) -> Result<FileStream> {
+        let sanitized_path = validate_and_sanitize_path(path)?;
+        let resolved_path = self.resolve_file_path(&sanitized_path).await?;
+        validate_path_within_base(&resolved_path, &self.upload_path)?;
+
+        let mut file = fs::File::open(&resolved_path).await?;
+        file.seek(std::io::SeekFrom::Start(start)).await?;
+        let length = end - start + 1;
+        Ok(FileStream {
+            size: Some(length),
+            chunks: Box::pin(ReaderStream::new(file.take(length))),
+        })
+    }
+
     async fn delete_document_files(&self, _user_id: Uuid, document_id: Uuid, filename: &str) -> Result<()> {
         let mut deleted_files = Vec::new();
         let mut serious_errors = Vec::new();
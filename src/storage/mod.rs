@@ -1,15 +1,93 @@
 //! Storage backend abstraction for document management
 //! 
 //! This module provides a clean abstraction over different storage backends
-//! (local filesystem, S3, etc.) with a unified interface.
+//! (local filesystem, S3, Azure Blob Storage) with a unified interface.
 
 use anyhow::Result;
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::{BoxStream, StreamExt};
+use std::path::Path;
 use uuid::Uuid;
 
+pub mod azure;
 pub mod local;
 pub mod factory;
 
+/// Directory or key prefix of content-addressed blobs, the files shared by every
+/// document with the same content
+pub const BLOBS_DIR: &str = "blobs";
+
+/// Directory or key prefix of document files organized by the admin's storage layout
+/// template, e.g. `library/2024/ACME/Invoice-{document_id}.pdf`
+pub const LIBRARY_DIR: &str = "library";
+
+/// Key of a new blob under `BLOBS_DIR`: `{hash[..2]}/{hash}-{nonce}.{ext}`. The nonce
+/// gives content stored again after its blob was deleted a new path.
+pub fn blob_key(hash: &str, filename: &str) -> String {
+    let nonce = &Uuid::new_v4().simple().to_string()[..8];
+    let prefix = hash.get(..2).unwrap_or("00");
+    let extension = Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| ext.len() <= 10 && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|ext| format!(".{}", ext.to_lowercase()))
+        .unwrap_or_default();
+    format!("{}/{}-{}{}", prefix, hash, nonce, extension)
+}
+
+/// Whether a stored path is a blob that other documents may share. Blobs are
+/// released through `FileService::release_blob`, never deleted directly.
+pub fn is_blob_path(path: &str) -> bool {
+    match path.strip_prefix("s3://").or_else(|| path.strip_prefix(azure::AZURE_PATH_PREFIX)) {
+        Some(key) => key.starts_with(&format!("{}/", BLOBS_DIR)),
+        None => Path::new(path).components().rev().nth(2) == Some(std::path::Component::Normal(std::ffi::OsStr::new(BLOBS_DIR))),
+    }
+}
+
+/// A stored file read as a stream of chunks instead of into memory
+pub struct FileStream {
+    /// Size in bytes, when the backend reports it
+    pub size: Option<u64>,
+    pub chunks: BoxStream<'static, std::io::Result<Bytes>>,
+}
+
+impl FileStream {
+    /// A stream of data that is already in memory
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self {
+            size: Some(data.len() as u64),
+            chunks: Box::pin(futures::stream::once(async move { Ok(Bytes::from(data)) })),
+        }
+    }
+
+    /// Only the bytes `start..=end` of the stream, read through from the beginning
+    pub fn slice(self, start: u64, end: u64) -> Self {
+        let chunks = futures::stream::unfold((self.chunks, 0u64), move |(mut chunks, mut position)| async move {
+            while position <= end {
+                let chunk = match chunks.next().await? {
+                    Ok(chunk) => chunk,
+                    Err(e) => return Some((Err(e), (chunks, end + 1))),
+                };
+                let chunk_start = position;
+                position += chunk.len() as u64;
+                if position <= start {
+                    continue;
+                }
+                let from = start.saturating_sub(chunk_start) as usize;
+                let to = (end + 1 - chunk_start).min(chunk.len() as u64) as usize;
+                return Some((Ok(chunk.slice(from..to)), (chunks, position)));
+            }
+            None
+        });
+
+        Self {
+            size: Some(end - start + 1),
+            chunks: Box::pin(chunks),
+        }
+    }
+}
+
 /// Core storage backend trait that all storage implementations must implement
 #[async_trait]
 pub trait StorageBackend: Send + Sync {
@@ -31,7 +109,47 @@ pub trait StorageBackend: Send + Sync {
     
     /// Retrieve file data by storage path/key
     async fn retrieve_file(&self, path: &str) -> Result<Vec<u8>>;
+
+    /// Store a document from a local file without reading it into memory.
+    /// Returns the storage path/key like `store_document`.
+    async fn store_document_from_path(&self, user_id: Uuid, document_id: Uuid, filename: &str, source: &Path) -> Result<String> {
+        let data = tokio::fs::read(source).await?;
+        self.store_document(user_id, document_id, filename, &data).await
+    }
+
+    /// Stream file data by storage path/key without reading it into memory
+    async fn retrieve_stream(&self, path: &str) -> Result<FileStream> {
+        Ok(FileStream::from_bytes(self.retrieve_file(path).await?))
+    }
+
+    /// Stream the bytes `start..=end` of a file, for HTTP range requests
+    async fn retrieve_range_stream(&self, path: &str, start: u64, end: u64) -> Result<FileStream> {
+        Ok(self.retrieve_stream(path).await?.slice(start, end))
+    }
     
+    /// Store a content-addressed blob under `BLOBS_DIR` with a key from `blob_key`.
+    /// Returns the storage path like `store_document`.
+    async fn store_content_blob(&self, key: &str, _data: &[u8]) -> Result<String> {
+        Err(anyhow::anyhow!("{} storage cannot store blob {}", self.storage_type(), key))
+    }
+
+    /// Delete a blob stored by `store_content_blob`
+    async fn delete_content_blob(&self, path: &str) -> Result<()> {
+        Err(anyhow::anyhow!("{} storage cannot delete blob {}", self.storage_type(), path))
+    }
+
+    /// Store a document file at `relative_path` under `LIBRARY_DIR`, as rendered from the
+    /// storage layout template. Returns the storage path like `store_document`.
+    async fn store_library_file(&self, relative_path: &str, _data: &[u8]) -> Result<String> {
+        Err(anyhow::anyhow!("{} storage cannot store {} in the library layout", self.storage_type(), relative_path))
+    }
+
+    /// Delete the single file at a storage path, leaving the document's thumbnail and
+    /// processed image in place
+    async fn delete_stored_file(&self, path: &str) -> Result<()> {
+        Err(anyhow::anyhow!("{} storage cannot delete file {}", self.storage_type(), path))
+    }
+
     /// Delete all files associated with a document (document, thumbnail, processed image)
     async fn delete_document_files(&self, user_id: Uuid, document_id: Uuid, filename: &str) -> Result<()>;
     
@@ -59,4 +177,27 @@ pub enum StorageConfig {
         /// Optional local fallback path for hybrid scenarios
         fallback_path: Option<String>,
     },
-}
\ No newline at end of file
+    /// Azure Blob Storage container
+    Azure {
+        azure_config: azure::AzureBlobConfig,
+    },
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_paths() {
+        let hash = "ab3f".repeat(16);
+        let key = blob_key(&hash, "Scan.PDF");
+        assert!(key.starts_with(&format!("ab/{}-", hash)));
+        assert!(key.ends_with(".pdf"));
+        assert!(!blob_key(&hash, "notes").contains('.'));
+
+        assert!(is_blob_path(&format!("./uploads/blobs/{}", key)));
+        assert!(is_blob_path(&format!("s3://blobs/{}", key)));
+        assert!(is_blob_path(&format!("azure://blobs/{}", key)));
+        assert!(!is_blob_path("./uploads/documents/5f0c.pdf"));
+        assert!(!is_blob_path("s3://documents/blobs/ab/x.pdf"));
+    }
+}
@@ -8,23 +8,25 @@ use crate::{
     models::{
         CreateUser, LoginRequest, LoginResponse, UserResponse, UpdateUser,
         DocumentResponse, SearchRequest, SearchResponse, EnhancedDocumentResponse,
-        SettingsResponse, UpdateSettings, SearchMode, SearchSnippet, HighlightRange,
-        FacetItem, SearchFacetsResponse, Notification, NotificationSummary, CreateNotification,
+        SettingsResponse, UpdateSettings, SearchMode, SearchField, SearchSnippet, HighlightRange,
+        FacetItem, SearchFacetsResponse, SearchResultFacets, Notification, NotificationSummary, CreateNotification,
         Source, SourceResponse, CreateSource, UpdateSource, SourceWithStats,
-        WebDAVSourceConfig, LocalFolderSourceConfig, S3SourceConfig,
+        WebDAVSourceConfig, WebDAVSyncDirection, LocalFolderSourceConfig, LocalFolderWatchMode, S3SourceConfig, S3NotificationConfig, ImapSourceConfig, ImapLabelRule, ImapProcessedAction, DropboxSourceConfig, OneDriveSourceConfig, SftpSourceConfig, FileTransferProtocol, RemoteProcessedAction, SimulateSourceRulesRequest, SourceRuleDecision, SourceRuleSimulation, SourceSyncPreview, SyncPreviewEntry, SyncPreviewAction, UpdateSourceScheduleRequest, SourceScheduleResponse, UpdateFilenameTemplatesRequest, TestFilenameTemplatesRequest, ParsedFilename, FilenameParsePreview, SourceSyncRun, SyncRunFileError, SourceListQuery, SourceSyncRunListQuery,
         ProcessedImage, CreateProcessedImage, IgnoredFileResponse, IgnoredFilesQuery,
         DocumentListResponse, DocumentOcrResponse, DocumentOperationResponse,
         BulkDeleteResponse, PaginationInfo, DocumentDuplicatesResponse
     },
     models::source::{
-        WebDAVCrawlEstimate, WebDAVTestConnection, WebDAVConnectionResult, WebDAVSyncStatus,
+        WebDAVCrawlEstimate, WebDAVTestConnection, WebDAVConnectionResult, WebDAVSyncStatus, WebDAVOutgoingChange,
     },
     routes::{
         metrics::{
             SystemMetrics, DatabaseMetrics, OcrMetrics, DocumentMetrics, UserMetrics, GeneralSystemMetrics
         },
         labels::{
-            Label, CreateLabel, UpdateLabel, LabelAssignment, LabelQuery, BulkUpdateRequest as LabelBulkUpdateRequest
+            Label, CreateLabel, UpdateLabel, LabelAssignment, LabelQuery, BulkUpdateRequest as LabelBulkUpdateRequest,
+            MergeLabelRequest, LabelMergeResponse, RenameLabelRequest, RenameLabelResponse, LabelUsage,
+            LabelSavedSearchUsage, UnusedLabelsQuery
         },
         documents::BulkDeleteRequest
     },
@@ -42,22 +44,107 @@ use crate::{
         crate::routes::auth::oidc_callback,
         // Document endpoints
         crate::routes::documents::crud::upload_document,
+        crate::routes::documents::capture::upload_capture,
+        crate::routes::uploads::get_upload_options,
+        crate::routes::uploads::create_upload,
+        crate::routes::uploads::get_upload_offset,
+        crate::routes::uploads::get_upload,
+        crate::routes::uploads::append_upload,
+        crate::routes::uploads::terminate_upload,
         crate::routes::documents::crud::list_documents,
         crate::routes::documents::crud::get_document_by_id,
         crate::routes::documents::crud::delete_document,
+        crate::routes::documents::crud::rename_document,
         crate::routes::documents::bulk::bulk_delete_documents,
+        crate::routes::documents::bulk::create_bulk_operation,
+        crate::routes::documents::bulk::list_bulk_operations,
+        crate::routes::documents::bulk::get_bulk_operation,
         crate::routes::documents::crud::download_document,
+        crate::routes::documents::archive::download_documents_zip,
         crate::routes::documents::crud::view_document,
+        crate::routes::documents::crud::preview_document,
         crate::routes::documents::debug::get_document_thumbnail,
         crate::routes::documents::ocr::get_document_ocr,
+        crate::routes::documents::ocr::update_document_ocr_text,
         crate::routes::documents::debug::get_processed_image,
         crate::routes::documents::ocr::retry_ocr,
         crate::routes::documents::debug::get_document_debug_info,
         crate::routes::documents::failed::get_failed_ocr_documents,
+        crate::routes::documents::failed::get_failed_documents,
         crate::routes::documents::failed::view_failed_document,
+        crate::routes::documents::failed::get_failed_document_history,
+        crate::routes::documents::failed::retry_failed_document,
         crate::routes::documents::bulk::delete_low_confidence_documents,
         crate::routes::documents::bulk::delete_failed_ocr_documents,
         crate::routes::documents::crud::get_user_duplicates,
+        crate::routes::documents::similar::get_similar_documents,
+        crate::routes::documents::similar::scan_similar_documents,
+        crate::routes::documents::similar::dismiss_similar_documents,
+        crate::routes::documents::similar::merge_duplicate_documents,
+        crate::routes::documents::versions::get_document_versions,
+        crate::routes::documents::versions::restore_document_version,
+        crate::routes::documents::versions::get_document_version_diff,
+        crate::routes::documents::compare::compare_documents,
+        crate::routes::documents::redaction::redact_document,
+        crate::routes::documents::pii::get_document_pii,
+        crate::routes::documents::pii::scan_document_pii,
+        crate::routes::documents::security::get_document_security,
+        crate::routes::documents::security::sanitize_document,
+        crate::routes::documents::signatures::get_document_signatures,
+        crate::routes::documents::signatures::verify_document_signatures,
+        crate::routes::documents::translations::translate_document,
+        crate::routes::documents::translations::list_document_translations,
+        crate::routes::documents::translations::get_document_translation,
+        crate::routes::documents::translations::delete_document_translation,
+        crate::routes::documents::audio::narrate_document,
+        crate::routes::documents::audio::get_document_audio,
+        crate::routes::documents::audio::get_document_audio_status,
+        crate::routes::documents::audio::delete_document_audio,
+        crate::routes::documents::annotations::list_document_annotations,
+        crate::routes::documents::annotations::create_document_annotation,
+        crate::routes::documents::annotations::update_document_annotation,
+        crate::routes::documents::annotations::delete_document_annotation,
+        crate::routes::documents::annotations::create_annotation_comment,
+        crate::routes::documents::annotations::update_annotation_comment,
+        crate::routes::documents::annotations::delete_annotation_comment,
+        crate::routes::documents::annotations::annotation_changes_websocket,
+        crate::routes::documents::progress::document_events,
+        crate::routes::documents::search::search_within_document,
+        crate::routes::documents::related::get_related_documents,
+        crate::routes::documents::artifacts::get_document_artifacts,
+        crate::routes::documents::artifacts::extract_document_artifacts,
+        crate::routes::documents::artifacts::search_page_artifacts,
+        crate::routes::documents::ocr::get_document_vision_pages,
+        crate::routes::documents::ocr::ocr_document_region,
+        crate::routes::documents::ocr::get_vision_quota,
+        crate::routes::documents::ocr_words::get_low_confidence_words,
+        crate::routes::documents::ocr_words::analyze_document_words,
+        crate::routes::documents::ocr_words::list_low_confidence_documents,
+        crate::routes::documents::relations::list_document_relations,
+        crate::routes::documents::relations::create_document_relation,
+        crate::routes::documents::relations::update_document_relation,
+        crate::routes::documents::relations::delete_document_relation,
+        crate::routes::documents::relations::suggest_document_relations,
+        crate::routes::documents::split::split_document,
+        crate::routes::documents::split::get_document_splits,
+        crate::routes::documents::forms::get_document_form_fields,
+        crate::routes::documents::forms::extract_document_form_fields,
+        crate::routes::documents::forms::list_form_templates,
+        crate::routes::documents::forms::delete_form_template,
+        crate::routes::documents::omr::get_document_omr,
+        crate::routes::documents::omr::extract_document_omr,
+        crate::routes::documents::titles::get_document_title,
+        crate::routes::documents::titles::generate_document_title,
+        crate::routes::documents::titles::restore_document_title,
+        crate::routes::documents::batch_upload::batch_upload_documents,
+        crate::routes::documents::pages::merge_documents,
+        crate::routes::documents::pages::reorder_document_pages,
+        crate::routes::documents::pages::get_document_page_transforms,
+        crate::routes::documents::pages::rotate_document_pages,
+        crate::routes::documents::pages::reset_document_page_transforms,
+        crate::routes::documents::pages::get_document_page_image,
+        crate::routes::documents::pages::get_document_searchable_pdf,
+        crate::routes::documents::attachments::get_document_attachments,
         // Labels endpoints
         crate::routes::labels::get_labels,
         crate::routes::labels::create_label,
@@ -69,13 +156,35 @@ use crate::{
         crate::routes::labels::add_document_label,
         crate::routes::labels::remove_document_label,
         crate::routes::labels::bulk_update_document_labels,
+        crate::routes::labels::get_label_rule,
+        crate::routes::labels::set_label_rule,
+        crate::routes::labels::delete_label_rule,
+        crate::routes::labels::apply_label_rules,
+        crate::routes::labels::get_label_usage,
+        crate::routes::labels::rename_label,
+        crate::routes::labels::merge_label,
+        crate::routes::labels::get_unused_labels,
         // Search endpoints
         crate::routes::search::search_documents,
         crate::routes::search::enhanced_search_documents,
         crate::routes::search::get_search_facets,
+        crate::routes::search::reindex_search,
+        crate::routes::search::list_reindex_runs,
+        crate::routes::search::get_reindex_run,
+        crate::routes::search::list_search_analyzers,
+        crate::routes::search::update_search_analyzer,
+        crate::routes::search::list_search_synonyms,
+        crate::routes::search::set_search_synonym,
+        crate::routes::search::delete_search_synonym,
+        crate::routes::search::get_external_search_status,
+        crate::routes::search::resync_external_search,
         // Settings endpoints
         crate::routes::settings::get_settings,
         crate::routes::settings::update_settings,
+        crate::routes::settings::list_client_preferences,
+        crate::routes::settings::get_client_preference,
+        crate::routes::settings::update_client_preference,
+        crate::routes::settings::delete_client_preference,
         // User endpoints
         crate::routes::users::list_users,
         crate::routes::users::create_user,
@@ -89,8 +198,63 @@ use crate::{
         crate::routes::queue::get_ocr_status,
         crate::routes::queue::pause_ocr_processing,
         crate::routes::queue::resume_ocr_processing,
+        crate::routes::queue::list_queue_controls,
+        crate::routes::queue::pause_queue,
+        crate::routes::queue::resume_queue,
+        crate::routes::queue::get_ocr_workers,
+        crate::routes::queue::update_ocr_workers,
+        crate::routes::queue::get_thumbnail_queue_stats,
+        crate::routes::queue::backfill_thumbnails,
+        // Consistency check endpoints
+        crate::routes::consistency::get_consistency_report,
+        crate::routes::consistency::run_consistency_check,
+        crate::routes::consistency::repair_consistency_issue,
+        // Storage migration endpoints
+        crate::routes::storage_layout::get_storage_layout,
+        crate::routes::storage_layout::update_storage_layout,
+        crate::routes::storage_layout::list_storage_relocations,
+        crate::routes::storage_layout::start_storage_relocation,
+        crate::routes::storage_layout::get_storage_relocation,
+        crate::routes::storage_migrations::list_storage_migrations,
+        crate::routes::storage_migrations::start_storage_migration,
+        crate::routes::storage_migrations::get_storage_migration,
+        crate::routes::storage_migrations::resume_storage_migration,
+        crate::routes::storage_compression::list_compression_policies,
+        crate::routes::storage_compression::create_compression_policy,
+        crate::routes::storage_compression::get_compression_policy,
+        crate::routes::storage_compression::update_compression_policy,
+        crate::routes::storage_compression::delete_compression_policy,
+        crate::routes::storage_compression::get_compression_summary,
+        crate::routes::storage_compression::download_compressed_original,
+        crate::routes::cluster::list_worker_nodes,
+        crate::routes::cluster::remove_worker_node,
+        // Export endpoints
+        crate::routes::export::create_export,
+        crate::routes::export::list_exports,
+        crate::routes::export::get_export,
+        crate::routes::export::download_export,
+        crate::routes::export::delete_export,
+        crate::routes::import::create_import,
+        crate::routes::import::list_imports,
+        crate::routes::import::get_import,
+        crate::routes::backups::list_backups,
+        crate::routes::backups::get_backup_status,
+        crate::routes::backups::create_backup,
+        crate::routes::quarantine::list_quarantined_files,
+        crate::routes::quarantine::download_quarantined_file,
+        crate::routes::quarantine::delete_quarantined_file,
+        crate::routes::quarantine::list_converter_quarantine,
+        crate::routes::quarantine::release_converter_quarantine,
+        crate::routes::ingestion_hooks::list_ingestion_hooks,
+        crate::routes::ingestion_hooks::create_ingestion_hook,
+        crate::routes::ingestion_hooks::get_ingestion_hook,
+        crate::routes::ingestion_hooks::update_ingestion_hook,
+        crate::routes::ingestion_hooks::delete_ingestion_hook,
         // Metrics endpoints
         crate::routes::metrics::get_system_metrics,
+        crate::routes::metrics::get_slow_operations,
+        crate::routes::metrics::clear_slow_operations,
+        crate::routes::metrics::get_processing_costs,
         crate::routes::prometheus_metrics::get_prometheus_metrics,
         // Notifications endpoints
         crate::routes::notifications::get_notifications,
@@ -98,6 +262,16 @@ use crate::{
         crate::routes::notifications::mark_notification_read,
         crate::routes::notifications::mark_all_notifications_read,
         crate::routes::notifications::delete_notification,
+        crate::routes::notifications::list_notification_channels,
+        crate::routes::notifications::create_notification_channel,
+        crate::routes::notifications::get_notification_channel,
+        crate::routes::notifications::update_notification_channel,
+        crate::routes::notifications::delete_notification_channel,
+        crate::routes::notifications::test_notification_channel,
+        crate::routes::notifications::get_digest_settings,
+        crate::routes::notifications::update_digest_settings,
+        crate::routes::notifications::preview_digest,
+        crate::routes::notifications::send_digest,
         // Sources endpoints
         crate::routes::sources::crud::list_sources,
         crate::routes::sources::crud::create_source,
@@ -109,11 +283,20 @@ use crate::{
         crate::routes::sources::sync::trigger_deep_scan,
         crate::routes::sources::sync::sync_progress_websocket,
         crate::routes::sources::sync::get_sync_status,
+        crate::routes::sources::s3_events::receive_s3_events,
+        crate::routes::sources::sync::list_outgoing_changes,
         crate::routes::sources::validation::test_connection,
         crate::routes::sources::validation::validate_source,
         crate::routes::sources::estimation::estimate_crawl,
         crate::routes::sources::estimation::estimate_crawl_with_config,
         crate::routes::sources::validation::test_connection_with_config,
+        crate::routes::sources::simulation::simulate_source_rules,
+        crate::routes::sources::schedule::get_source_schedule,
+        crate::routes::sources::schedule::update_source_schedule,
+        crate::routes::sources::runs::list_source_sync_runs,
+        crate::routes::sources::filename_templates::get_filename_templates,
+        crate::routes::sources::filename_templates::update_filename_templates,
+        crate::routes::sources::filename_templates::test_filename_templates,
         // WebDAV endpoints
         crate::routes::webdav::start_webdav_sync,
         crate::routes::webdav::cancel_webdav_sync,
@@ -124,24 +307,289 @@ use crate::{
         crate::routes::ocr::get_available_languages,
         crate::ocr::api::health_check,
         crate::ocr::api::perform_ocr,
+        crate::routes::ocr_comparisons::create_ocr_comparison,
+        crate::routes::ocr_comparisons::list_ocr_comparisons,
+        crate::routes::ocr_comparisons::get_ocr_comparison,
+        crate::routes::ocr_comparisons::get_ocr_comparison_documents,
+        crate::routes::ocr_comparisons::delete_ocr_comparison,
         // Ignored files endpoints
         crate::routes::ignored_files::list_ignored_files,
         crate::routes::ignored_files::get_ignored_file,
         crate::routes::ignored_files::delete_ignored_file,
         crate::routes::ignored_files::bulk_delete_ignored_files,
         crate::routes::ignored_files::get_ignored_files_stats,
+        // Event endpoints
+        crate::routes::events::list_event_schemas,
+        crate::routes::events::get_event_schema,
+        crate::routes::events::replay_events,
+        crate::routes::events::event_stream_websocket,
+        // Webhook endpoints
+        crate::routes::webhooks::list_webhook_endpoints,
+        crate::routes::webhooks::create_webhook_endpoint,
+        crate::routes::webhooks::get_webhook_endpoint,
+        crate::routes::webhooks::update_webhook_endpoint,
+        crate::routes::webhooks::delete_webhook_endpoint,
+        crate::routes::webhooks::rotate_webhook_secret,
+        crate::routes::webhooks::list_webhook_deliveries,
+        crate::routes::webhooks::retry_webhook_delivery,
+        // Encryption key endpoints
+        crate::routes::encryption::list_encryption_keys,
+        crate::routes::encryption::rotate_encryption_key,
+        crate::routes::encryption::list_encryption_key_events,
+        // Physical location endpoints
+        crate::routes::physical_locations::list_physical_locations,
+        crate::routes::physical_locations::create_physical_location,
+        crate::routes::physical_locations::get_physical_location,
+        crate::routes::physical_locations::update_physical_location,
+        crate::routes::physical_locations::delete_physical_location,
+        crate::routes::physical_locations::assign_physical_location,
+        crate::routes::physical_locations::list_physical_location_documents,
+        crate::routes::physical_locations::get_physical_location_stats,
+        crate::routes::physical_locations::get_retention_report,
+        // Retention policy endpoints
+        crate::routes::retention::list_retention_policies,
+        crate::routes::retention::create_retention_policy,
+        crate::routes::retention::get_retention_policy,
+        crate::routes::retention::update_retention_policy,
+        crate::routes::retention::delete_retention_policy,
+        crate::routes::retention::list_retention_reviews,
+        crate::routes::retention::approve_retention_review,
+        crate::routes::retention::exempt_retention_review,
+        crate::routes::retention::list_retention_actions,
+        crate::routes::client_sync::get_client_sync_changes,
+        crate::routes::client_sync::push_client_sync_changes,
+        // Audit log endpoints
+        crate::routes::audit::get_audit_log,
+        // Group and sharing endpoints
+        crate::routes::groups::list_groups,
+        crate::routes::groups::create_group,
+        crate::routes::groups::get_group,
+        crate::routes::groups::update_group,
+        crate::routes::groups::delete_group,
+        crate::routes::groups::list_group_members,
+        crate::routes::groups::add_group_member,
+        crate::routes::groups::remove_group_member,
+        crate::routes::shares::list_shared_documents,
+        crate::routes::shares::list_document_shares,
+        crate::routes::shares::share_document,
+        crate::routes::shares::unshare_document,
+        crate::routes::shares::list_label_shares,
+        crate::routes::shares::share_label,
+        crate::routes::shares::unshare_label,
+        crate::routes::shares::list_collection_shares,
+        crate::routes::shares::share_collection,
+        crate::routes::shares::unshare_collection,
+        crate::routes::collections::list_collections,
+        crate::routes::collections::create_collection,
+        crate::routes::collections::get_collection,
+        crate::routes::collections::update_collection,
+        crate::routes::collections::delete_collection,
+        crate::routes::collections::move_collection,
+        crate::routes::collections::reorder_collection,
+        crate::routes::collections::add_collection_documents,
+        crate::routes::collections::remove_collection_document,
+        crate::routes::collections::export_collection,
+        crate::routes::omr::list_omr_templates,
+        crate::routes::omr::create_omr_template,
+        crate::routes::omr::get_omr_template,
+        crate::routes::omr::update_omr_template,
+        crate::routes::omr::delete_omr_template,
+        crate::routes::omr::list_omr_results,
+        crate::routes::omr::get_omr_summary,
+        crate::routes::outbound_limits::list_outbound_limits,
+        crate::routes::outbound_limits::set_outbound_limit,
+        crate::routes::outbound_limits::reset_outbound_limit,
+        crate::routes::data_residency::get_data_residency,
+        // Public share link endpoints
+        crate::routes::share_links::create_share_link,
+        crate::routes::share_links::list_document_share_links,
+        crate::routes::share_links::list_share_links,
+        crate::routes::share_links::revoke_share_link,
+        crate::routes::share_links::get_public_share,
+        crate::routes::share_links::download_public_share,
+        crate::routes::share_links::download_public_share_with_password,
+        crate::routes::guest_portals::list_guest_portals,
+        crate::routes::guest_portals::create_guest_portal,
+        crate::routes::guest_portals::get_guest_portal,
+        crate::routes::guest_portals::update_guest_portal,
+        crate::routes::guest_portals::delete_guest_portal,
+        crate::routes::guest_portals::get_public_portal,
+        crate::routes::guest_portals::search_portal_documents,
+        crate::routes::guest_portals::get_portal_document,
+        crate::routes::guest_portals::download_portal_document,
+        crate::routes::i18n::list_locales,
+        crate::routes::i18n::reload_catalogs,
+        crate::routes::i18n::get_my_locale,
+        crate::routes::i18n::update_my_locale,
+        crate::routes::instance::get_instance,
+        crate::routes::instance::update_instance,
+        crate::routes::maintenance::get_maintenance,
+        crate::routes::maintenance::update_maintenance,
+        // API token endpoints
+        crate::routes::api_tokens::list_api_tokens,
+        crate::routes::api_tokens::create_api_token,
+        crate::routes::api_tokens::delete_api_token,
+        // Scan device endpoints
+        crate::routes::scan_devices::list_scan_devices,
+        crate::routes::scan_devices::create_scan_device,
+        crate::routes::scan_devices::update_scan_device,
+        crate::routes::scan_devices::reset_scan_device_password,
+        crate::routes::scan_devices::delete_scan_device,
+        // Scanner bridge endpoints
+        crate::routes::scan_bridges::list_scan_bridges,
+        crate::routes::scan_bridges::start_scan_bridge_pairing,
+        crate::routes::scan_bridges::collect_scan_bridge_token,
+        crate::routes::scan_bridges::get_scan_bridge_pairing,
+        crate::routes::scan_bridges::approve_scan_bridge,
+        crate::routes::scan_bridges::update_scan_bridge,
+        crate::routes::scan_bridges::delete_scan_bridge,
+        crate::routes::scan_bridges::get_current_scan_bridge,
+        crate::routes::scan_bridges::push_scan,
+        // Two-factor authentication routes
+        crate::routes::two_factor::get_two_factor_status,
+        crate::routes::two_factor::setup_two_factor,
+        crate::routes::two_factor::enable_two_factor,
+        crate::routes::two_factor::disable_two_factor,
+        crate::routes::two_factor::regenerate_recovery_codes,
+        crate::routes::two_factor::login_second_factor,
+        crate::routes::two_factor::reset_user_two_factor,
+        crate::routes::two_factor::get_auth_policy,
+        crate::routes::two_factor::update_auth_policy,
+        // Passkey routes
+        crate::routes::passkeys::list_passkeys,
+        crate::routes::passkeys::start_passkey_registration,
+        crate::routes::passkeys::finish_passkey_registration,
+        crate::routes::passkeys::rename_passkey,
+        crate::routes::passkeys::delete_passkey,
+        crate::routes::passkeys::start_passkey_login,
+        crate::routes::passkeys::finish_passkey_login,
+        // Session routes
+        crate::routes::sessions::list_sessions,
+        crate::routes::sessions::revoke_session,
+        crate::routes::sessions::revoke_other_sessions,
+        crate::routes::sessions::logout,
+        crate::routes::sessions::revoke_user_sessions,
+        crate::routes::sessions::impersonate_user,
+        // Saved search routes
+        crate::routes::saved_searches::list_saved_searches,
+        crate::routes::saved_searches::create_saved_search,
+        crate::routes::saved_searches::get_saved_search,
+        crate::routes::saved_searches::update_saved_search,
+        crate::routes::saved_searches::delete_saved_search,
+        // Correspondent routes
+        crate::routes::correspondents::list_correspondents,
+        crate::routes::correspondents::create_correspondent,
+        crate::routes::correspondents::get_correspondent,
+        crate::routes::correspondents::update_correspondent,
+        crate::routes::correspondents::delete_correspondent,
+        crate::routes::correspondents::assign_correspondent,
+        crate::routes::correspondents::match_correspondents,
+        crate::routes::correspondents::get_document_correspondent,
+        // Document type routes
+        crate::routes::document_types::list_document_types,
+        crate::routes::document_types::create_document_type,
+        crate::routes::document_types::get_document_type,
+        crate::routes::document_types::update_document_type,
+        crate::routes::document_types::delete_document_type,
+        crate::routes::document_types::get_document_custom_fields,
+        crate::routes::document_types::set_document_custom_fields,
+        crate::routes::document_types::clear_document_custom_fields,
+        // Invoice routes
+        crate::routes::invoices::extract_invoice,
+        crate::routes::invoices::export_invoices,
+        // Storage quota routes
+        crate::routes::storage_quotas::get_my_usage,
+        crate::routes::calendar::get_calendar_feed,
+        crate::routes::calendar::create_calendar_feed,
+        crate::routes::calendar::update_calendar_feed,
+        crate::routes::calendar::delete_calendar_feed,
+        crate::routes::calendar::get_calendar_feed_ics,
+        crate::routes::reminders::list_reminders,
+        crate::routes::reminders::list_document_reminders,
+        crate::routes::reminders::create_document_reminder,
+        crate::routes::reminders::update_reminder,
+        crate::routes::reminders::snooze_reminder,
+        crate::routes::reminders::complete_reminder,
+        crate::routes::reminders::delete_reminder,
+        crate::routes::expirations::list_upcoming_expirations,
+        crate::routes::expirations::get_document_expiration,
+        crate::routes::expirations::set_document_expiration,
+        crate::routes::expirations::delete_document_expiration,
+        crate::routes::expirations::extract_document_expiration,
+        crate::routes::workflow::get_counts,
+        crate::routes::workflow::list_documents,
+        crate::routes::workflow::transition_documents,
+        crate::routes::workflow::get_settings,
+        crate::routes::workflow::update_settings,
+        crate::routes::legal_holds::list_legal_holds,
+        crate::routes::legal_holds::create_legal_hold,
+        crate::routes::legal_holds::get_legal_hold,
+        crate::routes::legal_holds::add_documents,
+        crate::routes::legal_holds::remove_document,
+        crate::routes::legal_holds::release_legal_hold,
+        crate::routes::legal_holds::export_manifest,
+        crate::routes::embargoes::list_embargoes,
+        crate::routes::embargoes::embargo_documents,
+        crate::routes::embargoes::release_embargoes,
+        crate::routes::embargoes::get_document_embargo,
+        crate::routes::embargoes::get_source_embargo,
+        crate::routes::embargoes::set_source_embargo,
+        crate::routes::embargoes::delete_source_embargo,
+        crate::routes::analysis::estimate_analysis_batch,
+        crate::routes::analysis::create_analysis_batch,
+        crate::routes::analysis::list_analysis_batches,
+        crate::routes::analysis::get_analysis_batch,
+        crate::routes::analysis::cancel_analysis_batch,
+        crate::routes::llm::analyze_document,
+        crate::routes::llm::get_document_graph,
+        crate::routes::llm::list_graph_versions,
+        crate::routes::llm::rename_graph_node,
+        crate::routes::llm::delete_graph_node,
+        crate::routes::llm::delete_graph_edge,
+        crate::routes::llm::list_graph_corrections,
+        crate::routes::llm::delete_graph_correction,
+        crate::routes::llm::get_llm_cache_stats,
+        crate::routes::llm::invalidate_llm_cache,
+        crate::routes::llm::get_llm_debug_log_status,
+        crate::routes::llm::list_llm_debug_runs,
+        crate::routes::llm::get_llm_debug_run,
+        crate::routes::llm::clear_llm_debug_runs,
+        crate::routes::entities::list_entities,
+        crate::routes::entities::get_entity,
+        crate::routes::timeline::list_timeline,
+        crate::routes::timeline::get_document_timeline,
+        crate::routes::statistics::get_statistics,
+        crate::routes::statistics::get_dashboard,
+        crate::routes::statistics::update_dashboard,
+        crate::routes::statistics::get_access_statistics,
+        crate::routes::statistics::get_unused_documents,
+        crate::routes::storage_quotas::get_user_usage,
+        crate::routes::storage_quotas::set_user_quota,
+        crate::routes::storage_quotas::delete_user_quota,
+        // Workspace routes
+        crate::routes::workspaces::list_workspaces,
+        crate::routes::workspaces::list_my_workspaces,
+        crate::routes::workspaces::create_workspace,
+        crate::routes::workspaces::get_workspace,
+        crate::routes::workspaces::update_workspace,
+        crate::routes::workspaces::delete_workspace,
+        crate::routes::workspaces::list_workspace_members,
+        crate::routes::workspaces::set_workspace_member,
+        crate::routes::workspaces::remove_workspace_member,
         // Health check
         crate::health_check,
+        crate::routes::health::liveness,
+        crate::routes::health::readiness,
     ),
     components(
         schemas(
             CreateUser, LoginRequest, LoginResponse, UserResponse, UpdateUser,
             DocumentResponse, SearchRequest, SearchResponse, EnhancedDocumentResponse,
-            SettingsResponse, UpdateSettings, SearchMode, SearchSnippet, HighlightRange,
-            FacetItem, SearchFacetsResponse, Notification, NotificationSummary, CreateNotification,
+            SettingsResponse, UpdateSettings, SearchMode, SearchField, SearchSnippet, HighlightRange,
+            FacetItem, SearchFacetsResponse, SearchResultFacets, Notification, NotificationSummary, CreateNotification,
             Source, SourceResponse, CreateSource, UpdateSource, SourceWithStats,
-            WebDAVSourceConfig, LocalFolderSourceConfig, S3SourceConfig,
-            WebDAVCrawlEstimate, WebDAVTestConnection, WebDAVConnectionResult, WebDAVSyncStatus,
+            WebDAVSourceConfig, WebDAVSyncDirection, LocalFolderSourceConfig, LocalFolderWatchMode, S3SourceConfig, S3NotificationConfig, ImapSourceConfig, ImapLabelRule, ImapProcessedAction, DropboxSourceConfig, OneDriveSourceConfig, SftpSourceConfig, FileTransferProtocol, RemoteProcessedAction, SimulateSourceRulesRequest, SourceRuleDecision, SourceRuleSimulation, SourceSyncPreview, SyncPreviewEntry, SyncPreviewAction, UpdateSourceScheduleRequest, SourceScheduleResponse, UpdateFilenameTemplatesRequest, TestFilenameTemplatesRequest, ParsedFilename, FilenameParsePreview, SourceSyncRun, SyncRunFileError, SourceListQuery, SourceSyncRunListQuery,
+            WebDAVCrawlEstimate, WebDAVTestConnection, WebDAVConnectionResult, WebDAVSyncStatus, WebDAVOutgoingChange,
             ProcessedImage, CreateProcessedImage, IgnoredFileResponse, IgnoredFilesQuery,
             crate::routes::ignored_files::BulkDeleteIgnoredFilesRequest,
             crate::routes::ignored_files::IgnoredFilesStats,
@@ -149,14 +597,252 @@ use crate::{
             SystemMetrics, DatabaseMetrics, OcrMetrics, DocumentMetrics, UserMetrics, GeneralSystemMetrics,
             // Labels schemas
             Label, CreateLabel, UpdateLabel, LabelAssignment, LabelQuery, LabelBulkUpdateRequest,
+            MergeLabelRequest, LabelMergeResponse, RenameLabelRequest, RenameLabelResponse, LabelUsage,
+            LabelSavedSearchUsage, UnusedLabelsQuery,
+            crate::models::LabelRule, crate::models::SetLabelRuleRequest,
+            crate::models::ApplyLabelRulesQuery, crate::models::ApplyLabelRulesResponse,
             // Document schemas
             BulkDeleteRequest, DocumentListResponse, DocumentOcrResponse, DocumentOperationResponse,
-            BulkDeleteResponse, PaginationInfo, DocumentDuplicatesResponse, crate::routes::documents::RetryOcrRequest,
+            BulkDeleteResponse, PaginationInfo, DocumentDuplicatesResponse, crate::routes::documents::RetryOcrRequest, crate::routes::documents::RenameDocumentRequest,
+            crate::routes::documents::MergeDocumentsRequest, crate::routes::documents::ReorderPagesRequest,
+            crate::models::PageTransform, crate::models::RotatePagesRequest, crate::models::PageImageQuery,
+            crate::routes::documents::CaptureMetadata, crate::routes::documents::CapturePageMetadata,
+            crate::routes::documents::CapturePoint, crate::routes::documents::CaptureDevice,
+            crate::routes::documents::DownloadZipRequest,
+            crate::models::BulkOperation, crate::models::BulkOperationRequest, crate::models::BulkOperationKind,
+            crate::models::BulkOperationStatus, crate::models::BulkOperationParameters, crate::models::BulkOperationError,
+            crate::routes::documents::RegionOcrRequest, crate::routes::documents::RegionOcrResponse,
             // OCR schemas
             crate::routes::ocr::AvailableLanguagesResponse, crate::routes::ocr::LanguageInfo,
             crate::ocr::api::OcrHealthResponse, crate::ocr::api::OcrErrorResponse, crate::ocr::api::OcrRequest,
+            crate::models::OcrEngineConfig, crate::models::CreateOcrComparisonRequest, crate::models::OcrComparisonStatus,
+            crate::models::OcrComparison, crate::models::OcrComparisonDocument, crate::models::OcrEngineSide,
+            crate::models::OcrEngineSummary, crate::models::OcrComparisonClassSummary, crate::models::OcrComparisonReport,
             // Sync progress schemas
-            crate::services::sync_progress_tracker::SyncProgressInfo
+            crate::services::sync_progress_tracker::SyncProgressInfo,
+            // Event schemas
+            crate::models::EventType, crate::models::EventEnvelope, crate::models::EventSchemaResponse,
+            crate::models::ReplayEventsRequest, crate::models::ReplayEventsResponse,
+            crate::models::WebhookEndpoint, crate::models::WebhookEventFilter,
+            crate::models::CreateWebhookEndpointRequest, crate::models::UpdateWebhookEndpointRequest,
+            crate::models::WebhookEndpointWithSecret, crate::models::WebhookDelivery,
+            crate::models::WebhookDeliveryStatus, crate::models::WebhookDeliveryQuery,
+            crate::models::NotificationChannel, crate::models::NotificationChannelConfig,
+            crate::models::CreateNotificationChannelRequest, crate::models::UpdateNotificationChannelRequest,
+            crate::models::DigestFrequency, crate::models::DigestSettings, crate::models::UpdateDigestSettingsRequest,
+            crate::models::Digest, crate::models::DigestDocument, crate::models::DigestFailure,
+            crate::models::DigestSearchMatch, crate::models::DigestPreview,
+            // Export schemas
+            crate::models::Export, crate::models::ExportStatus, crate::models::ExportError,
+            crate::models::CreateExportRequest, crate::models::Import, crate::models::ImportStatus,
+            crate::models::Backup, crate::models::BackupStatus, crate::models::BackupKind, crate::models::BackupOverview,
+            // Resumable upload schemas
+            crate::models::ResumableUpload, crate::models::ResumableUploadStatus,
+            crate::models::QuarantinedFile, crate::models::QuarantineListQuery,
+            crate::models::ConverterQuarantineEntry, crate::models::ConverterQuarantineQuery,
+            crate::models::IngestionHookStage, crate::models::IngestionHookKind, crate::models::HookFailurePolicy,
+            crate::models::IngestionHook, crate::models::CreateIngestionHookRequest,
+            crate::models::UpdateIngestionHookRequest,
+            // Failed document schemas
+            crate::models::FailureCategory, crate::models::FailedDocumentAttempt,
+            crate::models::FailedDocumentHistory, crate::models::RequeueAction,
+            crate::models::FailedDocumentRequeue,
+            // Document progress schemas
+            crate::models::DocumentProgress, crate::models::ProgressStage, crate::models::ProgressStatus,
+            // Encryption key schemas
+            crate::models::EncryptionKey, crate::models::EncryptionKeyEvent, crate::models::RotateEncryptionKeyRequest,
+            crate::routes::encryption::EncryptionQuery,
+            // Health probe schemas
+            crate::routes::health::HealthReport, crate::routes::health::ComponentHealth, crate::routes::health::HealthStatus,
+            // Page artifact schemas
+            crate::models::PageArtifact, crate::models::ArtifactKind, crate::models::BoundingBox,
+            // OCR word confidence schemas
+            crate::models::OcrWordConfidence, crate::models::LowConfidenceWord, crate::models::LowConfidenceWordsResponse,
+            crate::models::LowConfidenceDocument,
+            // Vision fallback schemas
+            crate::models::VisionFallbackPage, crate::models::LlmQuotaStatus,
+            crate::models::LlmCacheStats, crate::models::InvalidateLlmCacheQuery,
+            crate::models::InvalidateLlmCacheResponse, crate::models::AnalyzeDocumentQuery,
+            crate::models::LlmDebugRun, crate::models::LlmDebugExchange, crate::models::LlmDebugRunDetail,
+            crate::models::ListLlmDebugRunsQuery, crate::models::ClearLlmDebugRunsQuery,
+            crate::models::ClearLlmDebugRunsResponse, crate::models::LlmDebugLogStatus,
+            // Document split schemas
+            crate::models::SplitMethod, crate::models::SplitDocumentRequest, crate::models::SplitDocumentResponse,
+            crate::models::SplitPart, crate::models::DocumentSplit,
+            // Form extraction schemas
+            crate::models::FormTemplate, crate::models::FormTemplateField, crate::models::DocumentFormFields,
+            // Email attachment schemas
+            crate::models::DocumentAttachment,
+            // Consistency check schemas
+            crate::models::ConsistencyReport, crate::models::ConsistencyIssue, crate::models::ConsistencyIssueKind,
+            crate::models::ConsistencyCheckRequest, crate::models::ConsistencyRepairAction,
+            crate::models::ConsistencyRepairRequest, crate::models::ConsistencyRepairResult,
+            // Storage migration schemas
+            crate::models::StorageMigration, crate::models::StorageMigrationStatus, crate::models::StorageBackendKind,
+            crate::models::StorageCompressionPolicy, crate::models::CompressionFormat,
+            crate::models::CreateStorageCompressionPolicyRequest, crate::models::UpdateStorageCompressionPolicyRequest,
+            crate::models::StorageCompressionSummary,
+            crate::models::StartStorageMigrationRequest, crate::models::StorageMigrationReport,
+            crate::models::StorageMigrationFailure,
+            // Storage layout schemas
+            crate::models::StorageLayout, crate::models::UpdateStorageLayoutRequest, crate::models::StorageLayoutUpdate,
+            crate::models::StorageRelocation,
+            // Physical location schemas
+            crate::models::PhysicalLocation, crate::models::CreatePhysicalLocationRequest,
+            crate::models::UpdatePhysicalLocationRequest, crate::models::AssignPhysicalLocationRequest,
+            crate::models::AssignPhysicalLocationResponse, crate::models::PhysicalLocationStats,
+            crate::models::PhysicalLocationStatsResponse, crate::models::RetentionDueDocument,
+            crate::models::RetentionLocationReport, crate::models::RetentionReport,
+            crate::models::ExternalSearchState, crate::models::ExternalSearchStatus,
+            crate::models::SearchAnalyzer, crate::models::UpdateSearchAnalyzerRequest,
+            crate::models::SearchAnalyzerUpdateResponse, crate::models::SearchSynonym,
+            crate::models::CreateSearchSynonymRequest, crate::models::SearchIndexRebuild,
+            crate::models::ClientSyncDocument, crate::models::TombstoneReason, crate::models::ClientSyncTombstone,
+            crate::models::ClientSyncChangesResponse, crate::models::ClientDocumentChange,
+            crate::models::ClientSyncPushRequest, crate::models::ClientChangeOutcome,
+            crate::models::ClientChangeResult, crate::models::ClientSyncPushResponse,
+            crate::models::SimilarDocument, crate::models::SimilarDocumentGroup, crate::models::SimilarDocumentsResponse,
+            crate::models::DismissSimilarDocumentsRequest, crate::models::MergeDuplicatesRequest,
+            crate::models::MergeDuplicatesResponse,
+            crate::models::DocumentVersionResponse, crate::models::DocumentVersionsResponse,
+            crate::models::DocumentVersionDiffQuery, crate::models::DiffLineKind, crate::models::DiffLine,
+            crate::models::DocumentVersionDiffResponse,
+            crate::models::DocumentCompareQuery, crate::models::PageDifference, crate::models::DocumentCompareResponse,
+            crate::models::RedactionRegion, crate::models::RedactDocumentRequest, crate::models::RedactDocumentResponse,
+            crate::models::PiiKind, crate::models::PiiFinding, crate::models::DocumentPiiResponse, crate::models::PiiScanQuery,
+            crate::models::PdfSignatureStatus, crate::models::PdfSignature, crate::models::PdfSignatureReport,
+            crate::models::PdfSecurityFindingKind, crate::models::PdfSecurityFinding, crate::models::PdfSecurityReport,
+            crate::models::AnnotationKind, crate::models::DocumentAnnotation, crate::models::AnnotationComment,
+            crate::models::AnnotationResponse, crate::models::AnnotationListQuery,
+            crate::models::CreateAnnotationRequest, crate::models::UpdateAnnotationRequest,
+            crate::models::CreateAnnotationCommentRequest, crate::models::UpdateAnnotationCommentRequest,
+            crate::models::AnnotationChange,
+            crate::models::DocumentSearchResponse,
+            crate::models::RelatedDocument, crate::models::RelatedDocumentsResponse,
+            crate::models::DocumentRelationType, crate::models::RelationDirection, crate::models::DocumentRelation,
+            crate::models::LinkedDocumentRelation, crate::models::CreateDocumentRelationRequest,
+            crate::models::UpdateDocumentRelationRequest, crate::models::DocumentRelationSuggestion,
+            crate::models::Collection, crate::models::CollectionListItem, crate::models::CollectionDocument,
+            crate::models::CollectionDetail, crate::models::CreateCollectionRequest, crate::models::UpdateCollectionRequest,
+            crate::models::MoveCollectionRequest, crate::models::CollectionDocumentsRequest,
+            crate::models::ReorderCollectionRequest,
+            crate::models::OmrRegionKind, crate::models::OmrRegion, crate::models::OmrTemplate,
+            crate::models::CreateOmrTemplateRequest, crate::models::UpdateOmrTemplateRequest,
+            crate::models::OmrRegionReading, crate::models::OmrResult, crate::models::OmrResultListItem,
+            crate::models::OmrResultsQuery, crate::models::OmrFieldCount, crate::models::OmrExtractRequest,
+            crate::models::OutboundScope, crate::models::OutboundLimit, crate::models::OutboundLimitStatus,
+            crate::models::ContentDestination, crate::models::DataResidencyMode, crate::models::DataResidencyPolicy,
+            crate::models::ContentDestinationStatus, crate::models::DataResidencyStatus,
+            crate::monitoring::slow_operations::OperationCategory,
+            crate::monitoring::slow_operations::RecordedOperation,
+            crate::monitoring::slow_operations::OperationCategoryReport,
+            crate::monitoring::slow_operations::SlowOperationsReport,
+            crate::models::DocumentProcessingCosts, crate::models::ProcessingCostSort,
+            crate::models::ProcessingCostQuery, crate::models::ProcessingCostTotals,
+            crate::models::UserProcessingCosts, crate::models::DocumentProcessingCostEntry,
+            crate::models::ProcessingCostReport,
+            crate::models::RetentionPolicy, crate::models::CreateRetentionPolicyRequest,
+            crate::models::UpdateRetentionPolicyRequest, crate::models::RetentionReview,
+            crate::models::RetentionReviewsQuery, crate::models::RetentionActionRecord,
+            crate::models::RetentionActionsQuery,
+            crate::models::AuditLogEntry, crate::models::AuditLogQuery, crate::models::AuditLogResponse,
+            crate::models::SharePermission, crate::models::UserGroup, crate::models::CreateUserGroupRequest,
+            crate::models::UpdateUserGroupRequest, crate::models::UserGroupMember, crate::models::AddGroupMemberRequest,
+            crate::models::Share, crate::models::CreateShareRequest, crate::models::SharedDocument,
+            crate::models::SharedDocumentsQuery,
+            crate::models::ShareLink, crate::models::CreateShareLinkRequest, crate::models::CreateShareLinkResponse,
+            crate::models::PublicShareLinkInfo, crate::models::ShareLinkPasswordRequest, crate::models::ShareLinksQuery,
+            crate::models::GuestPortal, crate::models::CreateGuestPortalRequest, crate::models::UpdateGuestPortalRequest,
+            crate::models::PublicGuestPortal, crate::models::GuestPortalSearchQuery, crate::models::GuestPortalDocument,
+            crate::models::GuestPortalSearchResponse, crate::models::GuestPortalDocumentDetail,
+            crate::models::LocaleInfo, crate::models::UserLocaleResponse, crate::models::UpdateUserLocaleRequest,
+            crate::models::ReloadCatalogsResponse, crate::models::InstanceInfo, crate::models::InstanceSettings,
+            crate::models::OperationControls, crate::models::UpdateMaintenanceRequest, crate::models::QueueKind,
+            crate::models::QueueControlStatus,
+            crate::models::UpdateInstanceSettingsRequest, crate::models::ClientPreference,
+            crate::models::UpdateClientPreferenceRequest, crate::models::AccessStatistics, crate::models::AccessBucket,
+            crate::models::AccessedDocument, crate::models::UserAccessStatistics, crate::models::UnusedDocument,
+            crate::models::UnusedDocumentsResponse,
+            crate::models::DocumentTitle,
+            crate::models::GeneratedTitleResponse,
+            crate::models::BatchUploadManifest,
+            crate::models::BatchUploadManifestEntry,
+            crate::models::BatchUploadFileStatus,
+            crate::models::BatchUploadFileResult,
+            crate::models::BatchUploadResponse,
+            crate::models::ApiToken, crate::models::ApiTokenScope, crate::models::CreateApiTokenRequest,
+            crate::models::CreateApiTokenResponse,
+            crate::models::ScanDevice, crate::models::CreateScanDeviceRequest, crate::models::UpdateScanDeviceRequest,
+            crate::models::ScanDeviceCredentials,
+            crate::models::ScanBridge, crate::models::StartScanBridgePairingRequest, crate::models::ScanBridgePairing,
+            crate::models::PendingScanBridgePairing, crate::models::ApproveScanBridgeRequest,
+            crate::models::ScanBridgeTokenRequest, crate::models::ScanBridgeCredentials,
+            crate::models::UpdateScanBridgeRequest, crate::models::ScanPushMetadata,
+            crate::models::TwoFactorStatus, crate::models::TwoFactorSetupResponse, crate::models::TwoFactorCodeRequest,
+            crate::models::EnableTwoFactorResponse, crate::models::DisableTwoFactorRequest,
+            crate::models::RecoveryCodesResponse, crate::models::TwoFactorChallenge, crate::models::TwoFactorLoginRequest,
+            crate::models::AuthPolicy, crate::models::UpdateAuthPolicyRequest,
+            crate::models::UserPasskey, crate::models::PasskeyChallengeResponse,
+            crate::models::FinishPasskeyRegistrationRequest, crate::models::StartPasskeyLoginRequest,
+            crate::models::FinishPasskeyLoginRequest, crate::models::RenamePasskeyRequest,
+            crate::models::UserSession,
+            crate::models::Impersonator,
+            crate::models::ImpersonateRequest,
+            crate::models::ImpersonationResponse,
+            crate::models::SavedSearch, crate::models::SavedSearchQuery,
+            crate::models::CreateSavedSearchRequest, crate::models::UpdateSavedSearchRequest,
+            crate::models::Correspondent, crate::models::CorrespondentWithCount,
+            crate::models::CreateCorrespondentRequest, crate::models::UpdateCorrespondentRequest,
+            crate::models::AssignCorrespondentRequest, crate::models::AssignCorrespondentResponse,
+            crate::models::MatchCorrespondentsResponse, crate::models::DocumentCorrespondent,
+            crate::models::DocumentType, crate::models::CustomFieldDefinition,
+            crate::models::CreateDocumentTypeRequest, crate::models::UpdateDocumentTypeRequest,
+            crate::models::DocumentCustomFields, crate::models::SetDocumentCustomFieldsRequest,
+            crate::models::InvoiceData, crate::models::InvoiceLineItem,
+            crate::models::InvoiceExtractionQuery, crate::models::InvoiceExtractionResponse,
+            crate::models::InvoiceExportQuery,
+            crate::models::Workspace, crate::models::WorkspaceRole, crate::models::WorkspaceSettings,
+            crate::models::WorkspaceMembership, crate::models::WorkspaceMember,
+            crate::models::CreateWorkspaceRequest, crate::models::UpdateWorkspaceRequest,
+            crate::models::SetWorkspaceMemberRequest,
+            crate::models::StorageUsage, crate::models::StorageQuotaSource, crate::models::SetStorageQuotaRequest,
+            crate::models::CalendarFeed, crate::models::CreateCalendarFeedResponse, crate::models::UpdateCalendarFeedRequest,
+            crate::models::ReminderStatus, crate::models::DocumentReminder, crate::models::ReminderListQuery,
+            crate::models::CreateReminderRequest, crate::models::UpdateReminderRequest, crate::models::SnoozeReminderRequest,
+            crate::models::DocumentExpiration, crate::models::SetDocumentExpirationRequest,
+            crate::models::UpcomingExpirationsQuery,
+            crate::models::WorkflowState, crate::models::WorkflowCounts, crate::models::WorkflowDocument,
+            crate::models::WorkflowDocumentsQuery, crate::models::WorkflowTransitionRequest,
+            crate::models::WorkflowTransitionResponse, crate::models::SkippedTransition, crate::models::WorkflowSettings,
+            crate::models::LegalHold, crate::models::CreateLegalHold, crate::models::LegalHoldDocumentsRequest,
+            crate::models::LegalHoldDocumentsResponse, crate::models::ManifestEntry, crate::models::LegalHoldManifest,
+            crate::models::DocumentEmbargo, crate::models::EmbargoDocumentsRequest, crate::models::EmbargoDocumentsResponse,
+            crate::models::ReleaseEmbargoesRequest, crate::models::ReleaseEmbargoesResponse, crate::models::SourceEmbargo,
+            crate::models::UpdateSourceEmbargo, crate::models::ListEmbargoesQuery,
+            crate::models::AnalysisBatchFilter, crate::models::AnalysisBatchRequest, crate::models::AnalysisCostEstimate,
+            crate::models::AnalysisBatchStatus, crate::models::AnalysisBatch,
+            crate::models::GraphVersion, crate::models::DocumentGraphNode, crate::models::DocumentGraphEdge,
+            crate::models::DocumentGraph, crate::models::GraphCorrectionKind, crate::models::GraphChange,
+            crate::models::GraphCorrection, crate::models::RenameGraphNodeRequest, crate::models::DocumentGraphQuery,
+            crate::models::GraphEntity, crate::models::GraphEntitySummary, crate::models::EntitiesQuery,
+            crate::models::EntityMention, crate::models::EntityAttributeValue, crate::models::EntityAttribute,
+            crate::models::RelationshipDirection, crate::models::EntityRelationship, crate::models::EntityTimelineBucket,
+            crate::models::EntityProfile, crate::models::DatePrecision, crate::models::TimelineEvent,
+            crate::models::TimelineQuery,
+            crate::models::StatisticsBucket, crate::models::StatisticsQuery, crate::models::IngestionBucket,
+            crate::models::OcrBucket, crate::models::StorageBucket, crate::models::CorrespondentCount,
+            crate::models::LabelCount, crate::models::UserStatistics, crate::models::Statistics,
+            crate::models::DashboardStatistic, crate::models::DashboardWidget, crate::models::Dashboard,
+            crate::models::UpdateDashboardRequest,
+            crate::models::OcrPageText, crate::models::UpdateOcrTextRequest,
+            crate::models::TranslationProvider, crate::models::DocumentTranslation,
+            crate::models::DocumentTranslationSummary, crate::models::TranslateQuery,
+            crate::models::AudioStatus, crate::models::DocumentAudio, crate::models::NarrateQuery,
+            // Cluster schemas
+            crate::models::NodeRole, crate::models::WorkerNode,
+            // Queue schemas
+            crate::routes::queue::UpdateOcrWorkersRequest,
+            crate::models::ThumbnailQueueStats, crate::models::ThumbnailBackfillResponse
         )
     ),
     tags(
@@ -173,7 +859,50 @@ use crate::{
         (name = "webdav", description = "WebDAV synchronization endpoints"),
         (name = "ignored_files", description = "Ignored files management endpoints"),
         (name = "ocr", description = "OCR service management endpoints"),
-        (name = "health", description = "Health check endpoint"),
+        (name = "events", description = "Versioned event schemas and replay endpoints"),
+        (name = "webhooks", description = "Webhook endpoints and their event subscriptions"),
+        (name = "encryption", description = "Per-user encryption keys, rotation and key usage audit"),
+        (name = "consistency", description = "Database and storage consistency checks"),
+        (name = "storage", description = "Migration of stored files between backends and layouts, and recompression of oversized scans"),
+        (name = "cluster", description = "Worker nodes sharing the database and the background tasks they run"),
+        (name = "export", description = "Full-library export archives for backup and migration, their restore, and scheduled backups"),
+        (name = "physical_locations", description = "Where paper originals are kept and which are due for destruction"),
+        (name = "retention", description = "Retention policies that archive or delete old documents, with a review queue"),
+        (name = "client_sync", description = "Differential sync for offline-capable clients"),
+        (name = "audit", description = "Audit log of significant user and system actions"),
+        (name = "groups", description = "Groups of users that documents and labels can be shared with"),
+        (name = "shares", description = "Sharing documents, labels and collections with users and groups"),
+        (name = "collections", description = "Ordered, nestable collections of documents such as case files, and their PDF export"),
+        (name = "outbound_limits", description = "Concurrency and rate limits on requests to the LLM and sync servers"),
+        (name = "data_residency", description = "Which external services may receive document content"),
+        (name = "omr", description = "Form templates with checkbox and field regions, and the data read from matching scans"),
+        (name = "share_links", description = "Public document links with expiry, password, download limit and watermark"),
+        (name = "guest_portals", description = "Labels and collections exposed read-only to visitors without an account"),
+        (name = "i18n", description = "Languages of notifications, digests and error messages, and their translation catalogs"),
+        (name = "instance", description = "Branding and defaults of this instance"),
+        (name = "maintenance", description = "Maintenance mode that rejects writes while reads go on"),
+        (name = "api_tokens", description = "Scoped personal access tokens for scanners and scripts"),
+        (name = "scan_devices", description = "Network scanners delivering into the FTP scan drop"),
+        (name = "scan_bridges", description = "Desktop bridge apps pushing pages from directly attached scanners"),
+        (name = "calendar", description = "iCalendar feeds of document due dates for calendar apps"),
+        (name = "reminders", description = "Reminders on documents, delivered as notifications, with snooze and complete"),
+        (name = "expirations", description = "Expiration and renewal dates of documents, with notices ahead of them"),
+        (name = "workflow", description = "Inbox review workflow: inbox, reviewed, filed and rejected documents"),
+        (name = "legal_holds", description = "Legal holds that freeze documents, with integrity manifests"),
+        (name = "embargoes", description = "Embargoes hiding documents from other users until a date or their release"),
+        (name = "analysis", description = "Knowledge-graph extraction, graph versions and corrections, entities, timelines and batch analysis"),
+        (name = "statistics", description = "Library statistics over time and user dashboards"),
+        (name = "two_factor", description = "TOTP two-factor login and recovery codes"),
+        (name = "passkeys", description = "WebAuthn passkeys for passwordless login"),
+        (name = "sessions", description = "Login sessions and remote logout"),
+        (name = "saved_searches", description = "Saved searches and notifications about new matches"),
+        (name = "correspondents", description = "Senders and recipients of documents and the rules that assign them"),
+        (name = "document_types", description = "Document types and the custom fields of their documents"),
+        (name = "invoices", description = "Invoice data extraction and CSV export"),
+        (name = "workspaces", description = "Isolated workspaces and their members in multi-tenant mode"),
+        (name = "quarantine", description = "Files the malware scanner refused, for admins"),
+        (name = "ingestion_hooks", description = "Commands and webhooks run before files are stored and after their OCR, for admins"),
+        (name = "health", description = "Health check, liveness and readiness endpoints"),
     ),
     modifiers(&SecurityAddon),
     info(
@@ -205,7 +934,11 @@ impl Modify for SecurityAddon {
 }
 
 pub fn create_swagger_router() -> Router<Arc<AppState>> {
+    let openapi = ApiDoc::openapi();
+    #[cfg(feature = "graphql")]
+    let openapi = openapi.merge_from(crate::routes::graphql::GraphqlApiDoc::openapi());
+
     SwaggerUi::new("/swagger-ui")
-        .url("/api-docs/openapi.json", ApiDoc::openapi())
+        .url("/api-docs/openapi.json", openapi)
         .into()
 }
\ No newline at end of file
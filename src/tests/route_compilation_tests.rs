@@ -27,6 +27,7 @@ mod tests {
                 oidc_email: None,
                 auth_provider: AuthProvider::Local,
             },
+            workspace_id: None,
         };
         
         // This function call would fail if there were compilation issues
@@ -0,0 +1,162 @@
+//! Conditional requests (RFC 9110, section 13): validators of served files and the
+//! `If-None-Match`, `If-Modified-Since` and `If-Range` checks against them, so clients
+//! and caches revalidate a stored copy instead of downloading the file again
+
+use axum::{
+    body::Body,
+    http::{
+        header::{CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED},
+        HeaderMap, HeaderValue, Response, StatusCode,
+    },
+};
+use chrono::{DateTime, Utc};
+
+/// Cache-Control of files served behind authentication: caches may keep a copy, but
+/// must revalidate it with the requester's credentials before each use
+pub const REVALIDATE: &str = "no-cache, must-revalidate";
+
+/// What identifies the representation of a file that a response carries
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validators {
+    /// Strong entity tag, quoted
+    pub etag: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+impl Validators {
+    /// Validators of a file with content hash `hash`. Representations derived from
+    /// the file, such as a thumbnail or a copy without metadata, name a `variant`
+    /// so that they get tags of their own.
+    pub fn for_content(hash: Option<&str>, variant: Option<&str>, last_modified: DateTime<Utc>) -> Self {
+        let etag = hash.filter(|hash| !hash.is_empty()).map(|hash| match variant {
+            Some(variant) => format!("\"{}-{}\"", hash, variant),
+            None => format!("\"{}\"", hash),
+        });
+        Self { etag, last_modified: Some(last_modified) }
+    }
+
+    /// Whether the client's copy is current and 304 Not Modified answers the request.
+    /// `If-Modified-Since` is only consulted without `If-None-Match`.
+    pub fn is_not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = header_str(headers, IF_NONE_MATCH) {
+            let Some(etag) = &self.etag else {
+                return false;
+            };
+            return if_none_match
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || weak_eq(tag, etag));
+        }
+        match (header_str(headers, IF_MODIFIED_SINCE).and_then(parse_http_date), self.last_modified) {
+            (Some(since), Some(last_modified)) => last_modified.timestamp() <= since.timestamp(),
+            _ => false,
+        }
+    }
+
+    /// Whether a `Range` header may be honoured: without `If-Range`, or when it names
+    /// this representation. Otherwise the whole file is served.
+    pub fn range_applies(&self, headers: &HeaderMap) -> bool {
+        let Some(if_range) = header_str(headers, IF_RANGE).map(str::trim) else {
+            return true;
+        };
+        if if_range.starts_with('"') || if_range.starts_with("W/") {
+            // Ranges need a strong match
+            return self.etag.as_deref() == Some(if_range);
+        }
+        match (parse_http_date(if_range), self.last_modified) {
+            (Some(date), Some(last_modified)) => date.timestamp() == last_modified.timestamp(),
+            _ => false,
+        }
+    }
+
+    /// Add `ETag` and `Last-Modified` to a response
+    pub fn write(&self, headers: &mut HeaderMap) {
+        if let Some(value) = self.etag.as_deref().and_then(|etag| HeaderValue::from_str(etag).ok()) {
+            headers.insert(ETAG, value);
+        }
+        if let Some(value) = self.last_modified.and_then(|date| HeaderValue::from_str(&format_http_date(date)).ok()) {
+            headers.insert(LAST_MODIFIED, value);
+        }
+    }
+
+    /// 304 Not Modified with the validators and `cache_control`
+    pub fn not_modified(&self, cache_control: &str) -> Result<Response<Body>, StatusCode> {
+        let mut response = Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(CACHE_CONTROL, cache_control)
+            .body(Body::empty())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        self.write(response.headers_mut());
+        Ok(response)
+    }
+}
+
+fn header_str(headers: &HeaderMap, name: axum::http::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Weak comparison of two entity tags: equal once any `W/` prefix is dropped
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+/// An IMF-fixdate such as `Sun, 06 Nov 1994 08:49:37 GMT`
+pub fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value.trim()).ok().map(|date| date.with_timezone(&Utc))
+}
+
+pub fn format_http_date(date: DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn headers(pairs: &[(axum::http::HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), HeaderValue::from_str(value).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_if_none_match_takes_precedence() {
+        let modified = Utc.with_ymd_and_hms(2025, 10, 15, 10, 30, 0).unwrap();
+        let validators = Validators::for_content(Some("abc123"), None, modified);
+        assert_eq!(validators.etag.as_deref(), Some("\"abc123\""));
+
+        assert!(validators.is_not_modified(&headers(&[(IF_NONE_MATCH, "\"old\", W/\"abc123\"")])));
+        assert!(validators.is_not_modified(&headers(&[(IF_NONE_MATCH, "*")])));
+        assert!(!validators.is_not_modified(&headers(&[
+            (IF_NONE_MATCH, "\"old\""),
+            (IF_MODIFIED_SINCE, "Wed, 15 Oct 2025 10:30:00 GMT"),
+        ])));
+        assert!(validators.is_not_modified(&headers(&[(IF_MODIFIED_SINCE, "Wed, 15 Oct 2025 10:30:00 GMT")])));
+        assert!(!validators.is_not_modified(&headers(&[(IF_MODIFIED_SINCE, "Wed, 15 Oct 2025 10:29:59 GMT")])));
+        assert!(!validators.is_not_modified(&HeaderMap::new()));
+
+        let thumbnail = Validators::for_content(Some("abc123"), Some("thumbnail"), modified);
+        assert!(!thumbnail.is_not_modified(&headers(&[(IF_NONE_MATCH, "\"abc123\"")])));
+    }
+
+    #[test]
+    fn test_if_range_needs_a_strong_match() {
+        let modified = Utc.with_ymd_and_hms(2025, 10, 15, 10, 30, 0).unwrap();
+        let validators = Validators::for_content(Some("abc123"), None, modified);
+        assert!(validators.range_applies(&HeaderMap::new()));
+        assert!(validators.range_applies(&headers(&[(IF_RANGE, "\"abc123\"")])));
+        assert!(!validators.range_applies(&headers(&[(IF_RANGE, "W/\"abc123\"")])));
+        assert!(!validators.range_applies(&headers(&[(IF_RANGE, "\"old\"")])));
+        assert!(validators.range_applies(&headers(&[(IF_RANGE, &format_http_date(modified))])));
+    }
+
+    #[test]
+    fn test_http_date_round_trip() {
+        let date = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap();
+        assert_eq!(format_http_date(date), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(date));
+        assert_eq!(parse_http_date("yesterday"), None);
+    }
+}
@@ -0,0 +1,163 @@
+//! Coordinates of geotagged documents and the location filters of searches
+
+use serde_json::Value;
+
+use super::search_query::QueryParseError;
+
+/// Mean radius of the earth, as used by `readur_distance_km`
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Kilometers per degree of latitude, for narrowing a radius search to a band
+pub const KM_PER_DEGREE_LATITUDE: f64 = 111.195;
+
+/// Half the circumference of the earth; every point is within it
+const MAX_RADIUS_KM: f64 = 20_015.1;
+
+/// Where a search looks for geotagged documents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LocationFilter {
+    /// Within `radius_km` of a point
+    Near { latitude: f64, longitude: f64, radius_km: f64 },
+    /// Inside a box; `west` is greater than `east` when it crosses the antimeridian
+    BoundingBox { south: f64, west: f64, north: f64, east: f64 },
+}
+
+/// The filters of a search's `near` and `bbox` parameters
+pub fn location_filters(near: Option<&str>, bbox: Option<&str>) -> Result<Vec<LocationFilter>, QueryParseError> {
+    let mut filters = Vec::new();
+    if let Some(near) = near.filter(|v| !v.trim().is_empty()) {
+        filters.push(parse_near(near)?);
+    }
+    if let Some(bbox) = bbox.filter(|v| !v.trim().is_empty()) {
+        filters.push(parse_bbox(bbox)?);
+    }
+    Ok(filters)
+}
+
+/// `lat,lon,radius`, the radius in kilometers unless it ends in `m` or `km`
+pub fn parse_near(value: &str) -> Result<LocationFilter, QueryParseError> {
+    let invalid = || QueryParseError(format!("'{}' is not lat,lon,radius", value));
+    let [latitude, longitude, radius] = split_parts(value).ok_or_else(invalid)?;
+    let latitude = parse_latitude(latitude).ok_or_else(invalid)?;
+    let longitude = parse_longitude(longitude).ok_or_else(invalid)?;
+
+    let radius = radius.to_ascii_lowercase();
+    let radius_km = if let Some(km) = radius.strip_suffix("km") {
+        km.trim().parse::<f64>().ok()
+    } else if let Some(meters) = radius.strip_suffix('m') {
+        meters.trim().parse::<f64>().ok().map(|m| m / 1000.0)
+    } else {
+        radius.parse::<f64>().ok()
+    };
+    let radius_km = radius_km
+        .filter(|r| r.is_finite() && *r > 0.0)
+        .ok_or_else(|| QueryParseError(format!("'{}' is not a positive radius", radius)))?;
+
+    Ok(LocationFilter::Near { latitude, longitude, radius_km: radius_km.min(MAX_RADIUS_KM) })
+}
+
+/// `south,west,north,east`, i.e. min lat, min lon, max lat, max lon
+pub fn parse_bbox(value: &str) -> Result<LocationFilter, QueryParseError> {
+    let invalid = || QueryParseError(format!("'{}' is not south,west,north,east", value));
+    let [south, west, north, east] = split_parts(value).ok_or_else(invalid)?;
+    let south = parse_latitude(south).ok_or_else(invalid)?;
+    let west = parse_longitude(west).ok_or_else(invalid)?;
+    let north = parse_latitude(north).ok_or_else(invalid)?;
+    let east = parse_longitude(east).ok_or_else(invalid)?;
+    if south > north {
+        return Err(QueryParseError(format!("South {} is north of north {}", south, north)));
+    }
+    Ok(LocationFilter::BoundingBox { south, west, north, east })
+}
+
+fn split_parts<const N: usize>(value: &str) -> Option<[&str; N]> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    parts.try_into().ok()
+}
+
+fn parse_latitude(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().filter(|v| (-90.0..=90.0).contains(v))
+}
+
+fn parse_longitude(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().filter(|v| (-180.0..=180.0).contains(v))
+}
+
+/// The position a document was captured at, from the `gps_latitude` and
+/// `gps_longitude` read from its file
+pub fn coordinates_from_metadata(metadata: &Value) -> Option<(f64, f64)> {
+    let latitude = metadata.get("gps_latitude")?.as_f64().filter(|v| (-90.0..=90.0).contains(v))?;
+    let longitude = metadata.get("gps_longitude")?.as_f64().filter(|v| (-180.0..=180.0).contains(v))?;
+    Some((latitude, longitude))
+}
+
+/// Great-circle distance by the haversine formula, as `readur_distance_km` computes it
+pub fn distance_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.min(1.0).sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_near() {
+        assert_eq!(
+            parse_near("48.137, 11.575, 2.5km"),
+            Ok(LocationFilter::Near { latitude: 48.137, longitude: 11.575, radius_km: 2.5 })
+        );
+        assert_eq!(
+            parse_near("48.137,11.575,500m"),
+            Ok(LocationFilter::Near { latitude: 48.137, longitude: 11.575, radius_km: 0.5 })
+        );
+        assert_eq!(
+            parse_near("-33.9,151.2,10"),
+            Ok(LocationFilter::Near { latitude: -33.9, longitude: 151.2, radius_km: 10.0 })
+        );
+        assert!(parse_near("91,0,1").is_err());
+        assert!(parse_near("0,181,1").is_err());
+        assert!(parse_near("0,0,-1").is_err());
+        assert!(parse_near("0,0").is_err());
+        assert!(parse_near("0,0,1,2").is_err());
+    }
+
+    #[test]
+    fn test_parse_bbox() {
+        assert_eq!(
+            parse_bbox("47.9,11.3,48.3,11.8"),
+            Ok(LocationFilter::BoundingBox { south: 47.9, west: 11.3, north: 48.3, east: 11.8 })
+        );
+        // Crossing the antimeridian
+        assert!(parse_bbox("-20,170,-10,-170").is_ok());
+        assert!(parse_bbox("48.3,11.3,47.9,11.8").is_err());
+        assert!(parse_bbox("a,b,c,d").is_err());
+    }
+
+    #[test]
+    fn test_location_filters() {
+        assert_eq!(location_filters(None, Some(" ")), Ok(Vec::new()));
+        assert_eq!(location_filters(Some("1,2,3"), Some("0,0,10,10")).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_coordinates_from_metadata() {
+        assert_eq!(
+            coordinates_from_metadata(&json!({"gps_latitude": 48.137, "gps_longitude": 11.575})),
+            Some((48.137, 11.575))
+        );
+        assert_eq!(coordinates_from_metadata(&json!({"gps_latitude": 48.137})), None);
+        assert_eq!(coordinates_from_metadata(&json!({"gps_latitude": "48", "gps_longitude": 11})), None);
+    }
+
+    #[test]
+    fn test_distance_km() {
+        // Munich to Berlin
+        let distance = distance_km((48.137, 11.575), (52.520, 13.405));
+        assert!((distance - 504.0).abs() < 2.0, "{}", distance);
+        assert!(distance_km((0.0, 179.9), (0.0, -179.9)) < 23.0);
+    }
+}
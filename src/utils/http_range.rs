@@ -0,0 +1,89 @@
+//! `Range` request headers (RFC 9110, section 14), for serving parts of files
+
+/// What a `Range` header asks of a representation of a known size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// Serve the whole representation: no usable range, or several ranges
+    Full,
+    /// Serve bytes `start..=end`
+    Partial { start: u64, end: u64 },
+    /// The range starts past the end; answer 416
+    Unsatisfiable,
+}
+
+/// Resolve a `Range` header against a representation of `total` bytes. Only a
+/// single byte range is served; anything else falls back to the whole representation.
+pub fn parse(header: &str, total: u64) -> ByteRange {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') || total == 0 {
+        return ByteRange::Full;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (first, last) = (first.trim(), last.trim());
+
+    if first.is_empty() {
+        // Suffix range: the last N bytes
+        return match last.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(length) => ByteRange::Partial { start: total.saturating_sub(length), end: total - 1 },
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let Ok(start) = first.parse::<u64>() else {
+        return ByteRange::Full;
+    };
+    let end = if last.is_empty() {
+        total - 1
+    } else {
+        match last.parse::<u64>() {
+            Ok(end) if end >= start => end.min(total - 1),
+            _ => return ByteRange::Full,
+        }
+    };
+    if start >= total {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial { start, end }
+}
+
+/// `Content-Range` value of a partial response
+pub fn content_range(start: u64, end: u64, total: u64) -> String {
+    format!("bytes {}-{}/{}", start, end, total)
+}
+
+/// `Content-Range` value of a 416 response
+pub fn unsatisfied_content_range(total: u64) -> String {
+    format!("bytes */{}", total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse("bytes=0-99", 1000), ByteRange::Partial { start: 0, end: 99 });
+        assert_eq!(parse("bytes=500-", 1000), ByteRange::Partial { start: 500, end: 999 });
+        assert_eq!(parse("bytes=-100", 1000), ByteRange::Partial { start: 900, end: 999 });
+        assert_eq!(parse("bytes=-5000", 1000), ByteRange::Partial { start: 0, end: 999 });
+        assert_eq!(parse("bytes=900-5000", 1000), ByteRange::Partial { start: 900, end: 999 });
+        assert_eq!(parse("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse("bytes=0-9,20-29", 1000), ByteRange::Full);
+        assert_eq!(parse("bytes=50-10", 1000), ByteRange::Full);
+        assert_eq!(parse("items=0-9", 1000), ByteRange::Full);
+        assert_eq!(parse("bytes=abc", 1000), ByteRange::Full);
+        assert_eq!(parse("bytes=0-", 0), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!(content_range(0, 99, 1000), "bytes 0-99/1000");
+        assert_eq!(unsatisfied_content_range(1000), "bytes */1000");
+    }
+}
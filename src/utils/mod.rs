@@ -1,2 +1,10 @@
+pub mod conditional;
 pub mod debug;
-pub mod security;
\ No newline at end of file
+pub mod geo;
+pub mod http_range;
+pub mod pagination;
+pub mod search_query;
+pub mod text_chunks;
+pub mod security;
+pub mod text_fold;
+pub mod zip_stream;
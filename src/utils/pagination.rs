@@ -0,0 +1,206 @@
+//! Cursor pagination of list endpoints. Items are ordered by a sort key with the id
+//! breaking ties, and a cursor names the last item of a page, so pages stay stable
+//! while items are added or removed. The next page is linked in a `Link` header
+//! (RFC 8288) and the number of matching items is sent in `X-Total-Count` unless the
+//! client opts out with `include_total=false`.
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Uri};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const TOTAL_COUNT_HEADER: HeaderName = HeaderName::from_static("x-total-count");
+
+/// Position after the last item of a page: its sort key and id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor<K> {
+    #[serde(rename = "k")]
+    pub key: K,
+    pub id: Uuid,
+}
+
+impl<K: Serialize + DeserializeOwned> Cursor<K> {
+    pub fn new(key: K, id: Uuid) -> Self {
+        Self { key, id }
+    }
+
+    /// Opaque, URL-safe form for the `cursor` parameter
+    pub fn encode(&self) -> String {
+        Base64UrlUnpadded::encode_string(&serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(encoded: &str) -> Option<Self> {
+        let bytes = Base64UrlUnpadded::decode_vec(encoded.trim()).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// The page a list request asks for
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRequest<K> {
+    pub limit: i64,
+    /// Start after this item
+    pub after: Option<Cursor<K>>,
+    /// Items to skip, for clients still paging with `offset`; 0 with a cursor
+    pub offset: i64,
+    pub include_total: bool,
+}
+
+impl<K: Serialize + DeserializeOwned> PageRequest<K> {
+    /// Read the pagination parameters of a list request. A cursor and an offset
+    /// cannot be combined, and a cursor of another list does not decode.
+    pub fn parse(
+        limit: Option<i64>,
+        default_limit: i64,
+        max_limit: i64,
+        cursor: Option<&str>,
+        offset: Option<i64>,
+        include_total: Option<bool>,
+    ) -> Result<Self, String> {
+        let after = match cursor.filter(|cursor| !cursor.trim().is_empty()) {
+            Some(cursor) => Some(Cursor::decode(cursor).ok_or_else(|| "Invalid cursor".to_string())?),
+            None => None,
+        };
+        let offset = offset.unwrap_or(0).max(0);
+        if after.is_some() && offset > 0 {
+            return Err("cursor and offset cannot be combined".to_string());
+        }
+
+        Ok(Self {
+            limit: limit.unwrap_or(default_limit).clamp(1, max_limit),
+            after,
+            offset,
+            include_total: include_total.unwrap_or(true),
+        })
+    }
+
+    /// Like [`PageRequest::parse`] for lists that are small enough to be returned whole,
+    /// which they are unless the client asks for a `limit` or passes a `cursor`
+    pub fn parse_optional(
+        limit: Option<i64>,
+        default_limit: i64,
+        max_limit: i64,
+        cursor: Option<&str>,
+        include_total: Option<bool>,
+    ) -> Result<Option<Self>, String> {
+        if limit.is_none() && cursor.is_none() {
+            return Ok(None);
+        }
+        Self::parse(limit, default_limit, max_limit, cursor, None, include_total).map(Some)
+    }
+
+    /// Rows to fetch: one more than the limit shows whether another page follows
+    pub fn fetch_limit(&self) -> i64 {
+        self.limit + 1
+    }
+}
+
+/// One page of a list, with the cursor of the page after it
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    /// Items matching the request across all pages, unless the client opted out
+    pub total: Option<i64>,
+}
+
+impl<T> Page<T> {
+    /// Make a page of rows fetched with [`PageRequest::fetch_limit`]
+    pub fn new<K: Serialize + DeserializeOwned>(
+        mut rows: Vec<T>,
+        limit: i64,
+        total: Option<i64>,
+        cursor_of: impl Fn(&T) -> Cursor<K>,
+    ) -> Self {
+        let has_more = rows.len() as i64 > limit;
+        rows.truncate(limit.max(0) as usize);
+        let next_cursor = if has_more { rows.last().map(|last| cursor_of(last).encode()) } else { None };
+        Self { items: rows, next_cursor, total }
+    }
+
+    /// A whole list as its only page
+    pub fn whole(items: Vec<T>) -> Self {
+        let total = Some(items.len() as i64);
+        Self { items, next_cursor: None, total }
+    }
+
+    pub fn has_more(&self) -> bool {
+        self.next_cursor.is_some()
+    }
+
+    /// `Link` to the next page and `X-Total-Count`, for a request to `uri`
+    pub fn headers(&self, uri: &Uri) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(cursor) = &self.next_cursor {
+            if let Ok(value) = HeaderValue::from_str(&format!("<{}>; rel=\"next\"", next_page_url(uri, cursor))) {
+                headers.insert(axum::http::header::LINK, value);
+            }
+        }
+        if let Some(total) = self.total {
+            headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+        }
+        headers
+    }
+}
+
+/// The request's URL with its `cursor` set to `cursor` and any `offset` removed
+pub fn next_page_url(uri: &Uri, cursor: &str) -> String {
+    let query = uri.query().unwrap_or_default();
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        if key != "cursor" && key != "offset" {
+            serializer.append_pair(&key, &value);
+        }
+    }
+    serializer.append_pair("cursor", cursor);
+    format!("{}?{}", uri.path(), serializer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor::new(Utc.with_ymd_and_hms(2025, 10, 15, 10, 30, 0).unwrap(), Uuid::new_v4());
+        let encoded = cursor.encode();
+        assert!(encoded.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(Cursor::<DateTime<Utc>>::decode(&encoded), Some(cursor));
+        assert_eq!(Cursor::<DateTime<Utc>>::decode("not a cursor"), None);
+    }
+
+    #[test]
+    fn test_page_request_rejects_cursor_with_offset() {
+        let cursor = Cursor::new("invoices".to_string(), Uuid::nil()).encode();
+        let request = PageRequest::<String>::parse(Some(500), 25, 100, Some(&cursor), None, Some(false)).unwrap();
+        assert_eq!((request.limit, request.offset, request.include_total), (100, 0, false));
+        assert!(PageRequest::<String>::parse(None, 25, 100, Some(&cursor), Some(20), None).is_err());
+        assert!(PageRequest::<String>::parse(None, 25, 100, Some("garbage"), None, None).is_err());
+
+        assert_eq!(PageRequest::<String>::parse_optional(None, 25, 100, None, None), Ok(None));
+        let request = PageRequest::<String>::parse_optional(None, 25, 100, Some(&cursor), None).unwrap().unwrap();
+        assert_eq!(request.limit, 25);
+    }
+
+    #[test]
+    fn test_page_links_the_next_one() {
+        let rows: Vec<(i64, Uuid)> = (0..3).map(|i| (i, Uuid::from_u128(i as u128))).collect();
+        let page = Page::new(rows, 2, Some(3), |&(key, id)| Cursor::new(key, id));
+        assert_eq!(page.items.len(), 2);
+        let cursor = page.next_cursor.clone().unwrap();
+        assert_eq!(Cursor::<i64>::decode(&cursor), Some(Cursor::new(1, Uuid::from_u128(1))));
+
+        let uri: Uri = "/api/documents?limit=2&offset=40&ocr_status=failed".parse().unwrap();
+        let headers = page.headers(&uri);
+        assert_eq!(
+            headers[axum::http::header::LINK],
+            format!("</api/documents?limit=2&ocr_status=failed&cursor={}>; rel=\"next\"", cursor)
+        );
+        assert_eq!(headers[TOTAL_COUNT_HEADER], "3");
+
+        let last = Page::new(vec![(5i64, Uuid::nil())], 2, None, |&(key, id)| Cursor::new(key, id));
+        assert!(!last.has_more());
+        assert!(last.headers(&uri).is_empty());
+    }
+}
@@ -0,0 +1,688 @@
+//! Query language of the search box. Besides plain words it understands
+//! `"quoted phrases"`, field filters such as `label:tax`, `source:webdav` or
+//! `created:>2023-01-01`, `OR`, `AND`, `NOT` or a leading `-` for exclusion, and
+//! parentheses. Terms are AND-ed unless joined with `OR`; NOT binds tighter than
+//! AND, and AND tighter than OR.
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use std::fmt;
+
+use crate::models::is_valid_field_name;
+
+/// Longest query that is parsed, in characters
+pub const MAX_QUERY_CHARS: usize = 1000;
+/// Groups and negations nested deeper than this are rejected; every level is a
+/// recursion of the parser and of the SQL built from the query
+const MAX_NESTING: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode {
+    /// Words that must all occur, in any order
+    Text(String),
+    /// Words that must occur next to each other
+    Phrase(String),
+    Field(FieldFilter),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+    Not(Box<QueryNode>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldFilter {
+    /// `label:`/`tag:` - a tag or label name, case-insensitive; a label also matches the labels nested under it
+    Label(String),
+    /// `source:` - name or type of the source, or how the document arrived (`web_upload`)
+    Source(String),
+    /// `correspondent:`/`from:` - correspondent name, case-insensitive
+    Correspondent(String),
+    /// `doctype:` - document type name, case-insensitive
+    DocumentType(String),
+    /// `field.<name>:` - value of a custom field
+    CustomField { name: String, condition: CustomFieldCondition },
+    /// `meta.<key>:` - value in the document's metadata, such as the author or camera
+    /// read from the file; text matches any part of the value
+    Metadata { key: String, condition: CustomFieldCondition },
+    /// `type:`/`mime:` - `application/pdf`, `image/*`, or one half of it such as `pdf`
+    MimeType(String),
+    /// `filename:` - part of the original filename
+    Filename(String),
+    /// `created:` - when the document was added
+    Created(DateRange),
+    /// `modified:` - when the document was last changed
+    Modified(DateRange),
+}
+
+/// How a `field.<name>:` filter tests a custom field's value
+#[derive(Debug, Clone, PartialEq)]
+pub enum CustomFieldCondition {
+    /// Equal to the text, ignoring case
+    Equals(String),
+    /// Exactly the value, such as an invoice number: `=INV-7`, or a bare number. A
+    /// number also matches the same digits stored as text.
+    Exact(String),
+    /// A number compared with `=`, `>`, `>=`, `<` or `<=`
+    Number { operator: &'static str, value: f64 },
+    /// A number from `min` to `max`, both included; a missing end is open
+    NumberRange { min: Option<f64>, max: Option<f64> },
+    /// A date within the range
+    Date(DateRange),
+}
+
+/// Times from `from` (inclusive) to `to` (exclusive); a missing end is open
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateRange {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryParseError(pub String);
+
+impl fmt::Display for QueryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for QueryParseError {}
+
+/// A parsed query. An empty query has no root and matches everything.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    pub root: Option<QueryNode>,
+}
+
+impl SearchQuery {
+    /// Whether the query is nothing but words, so any full-text engine can answer it
+    pub fn is_plain_text(&self) -> bool {
+        matches!(self.root, None | Some(QueryNode::Text(_)))
+    }
+
+    /// Words and phrases that are searched for rather than excluded, for ranking
+    /// and highlighting
+    pub fn positive_terms(&self) -> Vec<&str> {
+        let mut terms = Vec::new();
+        if let Some(root) = &self.root {
+            collect_positive_terms(root, &mut terms);
+        }
+        terms
+    }
+
+    /// Whether a `label:` filter anywhere in the query names the label, ignoring case
+    pub fn mentions_label(&self, name: &str) -> bool {
+        self.root.as_ref().is_some_and(|root| node_mentions_label(root, name))
+    }
+}
+
+fn node_mentions_label(node: &QueryNode, name: &str) -> bool {
+    match node {
+        QueryNode::Field(FieldFilter::Label(label)) => label.to_lowercase() == name.to_lowercase(),
+        QueryNode::And(nodes) | QueryNode::Or(nodes) => nodes.iter().any(|node| node_mentions_label(node, name)),
+        QueryNode::Not(node) => node_mentions_label(node, name),
+        _ => false,
+    }
+}
+
+fn collect_positive_terms<'a>(node: &'a QueryNode, terms: &mut Vec<&'a str>) {
+    match node {
+        QueryNode::Text(text) | QueryNode::Phrase(text) => terms.push(text),
+        QueryNode::And(nodes) | QueryNode::Or(nodes) => {
+            for node in nodes {
+                collect_positive_terms(node, terms);
+            }
+        }
+        QueryNode::Field(_) | QueryNode::Not(_) => {}
+    }
+}
+
+pub fn parse(query: &str) -> Result<SearchQuery, QueryParseError> {
+    if query.chars().count() > MAX_QUERY_CHARS {
+        return Err(QueryParseError(format!("Query is longer than {} characters", MAX_QUERY_CHARS)));
+    }
+    let tokens = tokenize(query)?;
+    let mut parser = Parser { tokens, position: 0, depth: 0 };
+
+    // Stray closing parentheses are skipped rather than rejected
+    let mut nodes = Vec::new();
+    loop {
+        if let Some(node) = parser.parse_or()? {
+            nodes.push(node);
+        }
+        if parser.next().is_none() {
+            break;
+        }
+    }
+
+    Ok(SearchQuery { root: combine_and(nodes) })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(QueryNode),
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, QueryParseError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        match c {
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '-' if chars.get(i + 1).is_some_and(|next| !next.is_whitespace()) => {
+                tokens.push(Token::Not);
+                i += 1;
+            }
+            '"' => {
+                let (phrase, end) = read_quoted(&chars, i + 1);
+                if !phrase.trim().is_empty() {
+                    tokens.push(Token::Term(QueryNode::Phrase(phrase.trim().to_string())));
+                }
+                i = end;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !matches!(chars[i], '(' | ')' | '"') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+
+                // field:"quoted value", or field:="quoted value" for an exact match
+                let quoted_field = word
+                    .strip_suffix(":=")
+                    .map(|field| (field, "="))
+                    .or_else(|| word.strip_suffix(':').map(|field| (field, "")));
+                if let Some((field, prefix)) = quoted_field.filter(|_| chars.get(i) == Some(&'"')) {
+                    let (value, end) = read_quoted(&chars, i + 1);
+                    i = end;
+                    match field_filter(field, &format!("{}{}", prefix, value.trim()))? {
+                        Some(filter) => tokens.push(Token::Term(QueryNode::Field(filter))),
+                        None if !value.trim().is_empty() => {
+                            tokens.push(Token::Term(QueryNode::Text(field.to_string())));
+                            tokens.push(Token::Term(QueryNode::Phrase(value.trim().to_string())));
+                        }
+                        None => tokens.push(Token::Term(QueryNode::Text(field.to_string()))),
+                    }
+                    continue;
+                }
+
+                match word.as_str() {
+                    "AND" | "&&" => tokens.push(Token::And),
+                    "OR" | "||" => tokens.push(Token::Or),
+                    "NOT" => tokens.push(Token::Not),
+                    _ => {
+                        let filter = match word.split_once(':') {
+                            Some((field, value)) => field_filter(field, value)?,
+                            None => None,
+                        };
+                        let node = match filter {
+                            Some(filter) => QueryNode::Field(filter),
+                            None => QueryNode::Text(word),
+                        };
+                        tokens.push(Token::Term(node));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Text up to the closing quote, or to the end when it is missing, and the index
+/// after the closing quote
+fn read_quoted(chars: &[char], start: usize) -> (String, usize) {
+    let mut end = start;
+    while end < chars.len() && chars[end] != '"' {
+        end += 1;
+    }
+    let text = chars[start..end].iter().collect();
+    (text, (end + 1).min(chars.len()))
+}
+
+/// The filter for a `field:value` term, or None when the field is not one the query
+/// language knows, in which case the term is searched for as text
+fn field_filter(field: &str, value: &str) -> Result<Option<FieldFilter>, QueryParseError> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let filter = match field.to_ascii_lowercase().as_str() {
+        "label" | "labels" | "tag" | "tags" => FieldFilter::Label(value.to_string()),
+        "source" => FieldFilter::Source(value.to_string()),
+        "correspondent" | "from" => FieldFilter::Correspondent(value.to_string()),
+        "doctype" | "document_type" => FieldFilter::DocumentType(value.to_string()),
+        "type" | "mime" => FieldFilter::MimeType(value.to_ascii_lowercase()),
+        "filename" | "file" | "name" => FieldFilter::Filename(value.to_string()),
+        "created" => FieldFilter::Created(parse_date_range(value)?),
+        "modified" | "updated" => FieldFilter::Modified(parse_date_range(value)?),
+        field => {
+            if let Some(name) = field.strip_prefix("field.").filter(|name| is_valid_field_name(name)) {
+                FieldFilter::CustomField {
+                    name: name.to_string(),
+                    condition: parse_custom_field_condition(value)?,
+                }
+            } else if let Some(key) = field.strip_prefix("meta.").filter(|key| is_valid_field_name(key)) {
+                FieldFilter::Metadata {
+                    key: key.to_string(),
+                    condition: parse_custom_field_condition(value)?,
+                }
+            } else {
+                return Ok(None);
+            }
+        }
+    };
+    Ok(Some(filter))
+}
+
+/// `=value` matches exactly; a number, optionally after `>`, `>=`, `<` or `<=`,
+/// compares numerically, and `min..max` with numbers is a numeric range; a date or
+/// date range as for `created:` compares dates; anything else is matched as text
+pub fn parse_custom_field_condition(value: &str) -> Result<CustomFieldCondition, QueryParseError> {
+    if let Some(exact) = value.strip_prefix('=') {
+        if exact.is_empty() {
+            return Err(QueryParseError("Nothing to match after '='".to_string()));
+        }
+        return Ok(CustomFieldCondition::Exact(exact.to_string()));
+    }
+    if let Some(range) = parse_number_range(value) {
+        return Ok(range);
+    }
+
+    let (operator, operand) = [">=", "<=", ">", "<"]
+        .into_iter()
+        .find_map(|operator| value.strip_prefix(operator).map(|operand| (operator, operand)))
+        .unwrap_or(("=", value));
+
+    if let Some(number) = parse_number(operand) {
+        // Invoice numbers and the like are often digits stored as text
+        if operator == "=" {
+            return Ok(CustomFieldCondition::Exact(value.to_string()));
+        }
+        return Ok(CustomFieldCondition::Number { operator, value: number });
+    }
+    if let Ok(range) = parse_date_range(value) {
+        return Ok(CustomFieldCondition::Date(range));
+    }
+    if operator != "=" {
+        return Err(QueryParseError(format!("'{}' is not a number or a date", operand)));
+    }
+    Ok(CustomFieldCondition::Equals(value.to_string()))
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    text.parse::<f64>().ok().filter(|number| number.is_finite())
+}
+
+/// `min..max`, `min..` or `..max` with numbers, None for anything else such as dates
+fn parse_number_range(value: &str) -> Option<CustomFieldCondition> {
+    let (min, max) = value.split_once("..")?;
+    let bound = |text: &str| if text.is_empty() { Some(None) } else { parse_number(text).map(Some) };
+    let (min, max) = (bound(min)?, bound(max)?);
+    if min.is_none() && max.is_none() {
+        return None;
+    }
+    Some(CustomFieldCondition::NumberRange { min, max })
+}
+
+/// `2023-01-01`, `2023-01` or `2023` for that period, `>`, `>=`, `<` and `<=` against
+/// one, or `from..to` with either end optional
+pub fn parse_date_range(value: &str) -> Result<DateRange, QueryParseError> {
+    if let Some((from, to)) = value.split_once("..") {
+        let from = if from.is_empty() { None } else { Some(parse_period(from)?.0) };
+        let to = if to.is_empty() { None } else { Some(parse_period(to)?.1) };
+        return Ok(DateRange { from, to });
+    }
+
+    let range = if let Some(date) = value.strip_prefix(">=") {
+        DateRange { from: Some(parse_period(date)?.0), to: None }
+    } else if let Some(date) = value.strip_prefix("<=") {
+        DateRange { from: None, to: Some(parse_period(date)?.1) }
+    } else if let Some(date) = value.strip_prefix('>') {
+        DateRange { from: Some(parse_period(date)?.1), to: None }
+    } else if let Some(date) = value.strip_prefix('<') {
+        DateRange { from: None, to: Some(parse_period(date)?.0) }
+    } else {
+        let (from, to) = parse_period(value)?;
+        DateRange { from: Some(from), to: Some(to) }
+    };
+    Ok(range)
+}
+
+/// Start and (exclusive) end of a year, month or day
+fn parse_period(value: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), QueryParseError> {
+    let invalid = || QueryParseError(format!("'{}' is not a date; use YYYY, YYYY-MM or YYYY-MM-DD", value));
+    let parts: Vec<&str> = value.split('-').collect();
+    let numbers: Vec<u32> = parts
+        .iter()
+        .map(|part| part.parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+
+    let (start, end) = match numbers.as_slice() {
+        [year] if parts[0].len() == 4 => (
+            NaiveDate::from_ymd_opt(*year as i32, 1, 1),
+            NaiveDate::from_ymd_opt(*year as i32 + 1, 1, 1),
+        ),
+        [year, month] if parts[0].len() == 4 => {
+            let (next_year, next_month) = if *month == 12 { (*year + 1, 1) } else { (*year, month + 1) };
+            (
+                NaiveDate::from_ymd_opt(*year as i32, *month, 1),
+                NaiveDate::from_ymd_opt(next_year as i32, next_month, 1),
+            )
+        }
+        [year, month, day] if parts[0].len() == 4 => {
+            let start = NaiveDate::from_ymd_opt(*year as i32, *month, *day);
+            (start, start.and_then(|date| date.succ_opt()))
+        }
+        _ => (None, None),
+    };
+
+    let to_utc = |date: NaiveDate| date.and_hms_opt(0, 0, 0).map(|time| Utc.from_utc_datetime(&time));
+    match (start.and_then(to_utc), end.and_then(to_utc)) {
+        (Some(start), Some(end)) => Ok((start, end)),
+        _ => Err(invalid()),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Groups and negations open at the current token
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Option<QueryNode>, QueryParseError> {
+        let mut nodes: Vec<QueryNode> = self.parse_and()?.into_iter().collect();
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            nodes.extend(self.parse_and()?);
+        }
+        Ok(match nodes.len() {
+            0 => None,
+            1 => nodes.pop(),
+            _ => Some(QueryNode::Or(nodes)),
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Option<QueryNode>, QueryParseError> {
+        let mut nodes = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(Token::RParen) | Some(Token::Or) => break,
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(_) => nodes.extend(self.parse_unary()?),
+            }
+        }
+        Ok(combine_and(nodes))
+    }
+
+    fn parse_unary(&mut self) -> Result<Option<QueryNode>, QueryParseError> {
+        let Some(token) = self.next() else {
+            return Ok(None);
+        };
+        match token {
+            Token::Not => {
+                let node = self.nested(Self::parse_unary)?;
+                Ok(node.map(|node| QueryNode::Not(Box::new(node))))
+            }
+            Token::LParen => {
+                let node = self.nested(Self::parse_or)?;
+                // An unclosed group runs to the end of the query
+                if self.peek() == Some(&Token::RParen) {
+                    self.next();
+                }
+                Ok(node)
+            }
+            Token::Term(node) => Ok(Some(node)),
+            Token::RParen | Token::And | Token::Or => Ok(None),
+        }
+    }
+
+    /// Parse one level deeper, refusing queries nested beyond `MAX_NESTING`
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Option<QueryNode>, QueryParseError>,
+    ) -> Result<Option<QueryNode>, QueryParseError> {
+        if self.depth >= MAX_NESTING {
+            return Err(QueryParseError(format!("Query nests groups or NOT more than {} levels deep", MAX_NESTING)));
+        }
+        self.depth += 1;
+        let node = parse(self);
+        self.depth -= 1;
+        node
+    }
+}
+
+/// AND-combines nodes, joining neighbouring plain words into one text term
+fn combine_and(nodes: Vec<QueryNode>) -> Option<QueryNode> {
+    let mut combined: Vec<QueryNode> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        match (combined.last_mut(), node) {
+            (Some(QueryNode::Text(previous)), QueryNode::Text(text)) => {
+                previous.push(' ');
+                previous.push_str(&text);
+            }
+            (_, node) => combined.push(node),
+        }
+    }
+    match combined.len() {
+        0 => None,
+        1 => combined.pop(),
+        _ => Some(QueryNode::And(combined)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> QueryNode {
+        QueryNode::Text(value.to_string())
+    }
+
+    fn label(value: &str) -> QueryNode {
+        QueryNode::Field(FieldFilter::Label(value.to_string()))
+    }
+
+    fn date(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_plain_words_stay_one_text_term() {
+        let query = parse("  invoice march ").unwrap();
+        assert_eq!(query.root, Some(text("invoice march")));
+        assert!(query.is_plain_text());
+        assert_eq!(parse("").unwrap().root, None);
+    }
+
+    #[test]
+    fn test_fields_phrases_and_exclusion() {
+        let query = parse(r#"label:tax "annual report" -draft source:webdav"#).unwrap();
+        assert_eq!(
+            query.root,
+            Some(QueryNode::And(vec![
+                label("tax"),
+                QueryNode::Phrase("annual report".to_string()),
+                QueryNode::Not(Box::new(text("draft"))),
+                QueryNode::Field(FieldFilter::Source("webdav".to_string())),
+            ]))
+        );
+        assert!(!query.is_plain_text());
+        assert_eq!(query.positive_terms(), vec!["annual report"]);
+
+        assert_eq!(parse(r#"label:"tax 2023""#).unwrap().root, Some(label("tax 2023")));
+        assert_eq!(
+            parse(r#"from:"ACME Corp""#).unwrap().root,
+            Some(QueryNode::Field(FieldFilter::Correspondent("ACME Corp".to_string())))
+        );
+        // Unknown fields and URLs are searched for as text
+        assert_eq!(parse("https://example.com").unwrap().root, Some(text("https://example.com")));
+    }
+
+    #[test]
+    fn test_boolean_precedence_and_groups() {
+        let query = parse("a b OR c NOT d").unwrap();
+        assert_eq!(
+            query.root,
+            Some(QueryNode::Or(vec![
+                text("a b"),
+                QueryNode::And(vec![text("c"), QueryNode::Not(Box::new(text("d")))]),
+            ]))
+        );
+
+        let query = parse("(label:tax OR label:bank) -(draft OR copy)").unwrap();
+        assert_eq!(
+            query.root,
+            Some(QueryNode::And(vec![
+                QueryNode::Or(vec![label("tax"), label("bank")]),
+                QueryNode::Not(Box::new(QueryNode::Or(vec![text("draft"), text("copy")]))),
+            ]))
+        );
+
+        // Unbalanced parentheses and dangling operators are tolerated
+        assert_eq!(parse("(a OR b").unwrap().root, Some(QueryNode::Or(vec![text("a"), text("b")])));
+        assert_eq!(parse("a) OR").unwrap().root, Some(text("a")));
+    }
+
+    #[test]
+    fn test_deep_nesting_and_long_queries_are_rejected() {
+        let nested = format!("{}tax{}", "(".repeat(MAX_NESTING), ")".repeat(MAX_NESTING));
+        assert_eq!(parse(&nested).unwrap().root, Some(text("tax")));
+
+        let too_deep = format!("{}tax{}", "(".repeat(MAX_NESTING + 1), ")".repeat(MAX_NESTING + 1));
+        assert!(parse(&too_deep).is_err());
+        assert!(parse(&format!("{}tax", "NOT ".repeat(MAX_NESTING + 1))).is_err());
+        // Far beyond the stack, as long as the query fits the length limit
+        assert!(parse(&"(".repeat(MAX_QUERY_CHARS)).is_err());
+
+        assert!(parse(&"a".repeat(MAX_QUERY_CHARS)).is_ok());
+        assert!(parse(&"a".repeat(MAX_QUERY_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_date_ranges() {
+        let range = parse_date_range(">2023-01-01").unwrap();
+        assert_eq!(range.from, Some(date("2023-01-02T00:00:00Z")));
+        assert_eq!(range.to, None);
+
+        let range = parse_date_range("<=2023-02").unwrap();
+        assert_eq!(range.from, None);
+        assert_eq!(range.to, Some(date("2023-03-01T00:00:00Z")));
+
+        let range = parse_date_range("2023").unwrap();
+        assert_eq!(range.from, Some(date("2023-01-01T00:00:00Z")));
+        assert_eq!(range.to, Some(date("2024-01-01T00:00:00Z")));
+
+        let range = parse_date_range("2023-12..2024-01-15").unwrap();
+        assert_eq!(range.from, Some(date("2023-12-01T00:00:00Z")));
+        assert_eq!(range.to, Some(date("2024-01-16T00:00:00Z")));
+
+        assert!(parse_date_range("2023-13-01").is_err());
+        assert!(parse_date_range("yesterday").is_err());
+        assert!(parse("created:>last-week").is_err());
+    }
+
+    #[test]
+    fn test_custom_field_filters() {
+        let condition = |query: &str| match parse(query).unwrap().root {
+            Some(QueryNode::Field(FieldFilter::CustomField { condition, .. })) => condition,
+            other => panic!("not a custom field filter: {:?}", other),
+        };
+
+        assert_eq!(
+            condition("field.total:>=100"),
+            CustomFieldCondition::Number { operator: ">=", value: 100.0 }
+        );
+        assert_eq!(
+            condition("field.due_date:<2024-07"),
+            CustomFieldCondition::Date(DateRange { from: None, to: Some(date("2024-07-01T00:00:00Z")) })
+        );
+        assert_eq!(
+            condition(r#"field.vendor:"ACME Corp""#),
+            CustomFieldCondition::Equals("ACME Corp".to_string())
+        );
+        assert_eq!(
+            condition("field.total:100..250.5"),
+            CustomFieldCondition::NumberRange { min: Some(100.0), max: Some(250.5) }
+        );
+        assert_eq!(condition("field.total:..50"), CustomFieldCondition::NumberRange { min: None, max: Some(50.0) });
+        assert_eq!(
+            condition("field.due_date:2024-01..2024-03"),
+            CustomFieldCondition::Date(DateRange {
+                from: Some(date("2024-01-01T00:00:00Z")),
+                to: Some(date("2024-04-01T00:00:00Z")),
+            })
+        );
+        assert_eq!(condition("field.invoice_number:=INV-2024-007"), CustomFieldCondition::Exact("INV-2024-007".to_string()));
+        assert_eq!(condition(r#"field.invoice_number:="INV 7""#), CustomFieldCondition::Exact("INV 7".to_string()));
+        assert_eq!(condition("field.invoice_number:10042"), CustomFieldCondition::Exact("10042".to_string()));
+        assert!(parse("field.total:>lots").is_err());
+        assert!(parse("field.total:=").is_err());
+        assert_eq!(parse("field.due-date:2024").unwrap().root, Some(text("field.due-date:2024")));
+        assert_eq!(
+            parse("doctype:Invoice").unwrap().root,
+            Some(QueryNode::Field(FieldFilter::DocumentType("Invoice".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_metadata_filters() {
+        assert_eq!(
+            parse("meta.camera_model:canon").unwrap().root,
+            Some(QueryNode::Field(FieldFilter::Metadata {
+                key: "camera_model".to_string(),
+                condition: CustomFieldCondition::Equals("canon".to_string()),
+            }))
+        );
+        assert_eq!(
+            parse("meta.taken_at:2023-06").unwrap().root,
+            Some(QueryNode::Field(FieldFilter::Metadata {
+                key: "taken_at".to_string(),
+                condition: CustomFieldCondition::Date(DateRange {
+                    from: Some(date("2023-06-01T00:00:00Z")),
+                    to: Some(date("2023-07-01T00:00:00Z")),
+                }),
+            }))
+        );
+        assert_eq!(parse("meta.camera-model:x").unwrap().root, Some(text("meta.camera-model:x")));
+    }
+
+    #[test]
+    fn test_mentions_label() {
+        let query = parse(r#"invoice (label:"Tax 2023" OR -tag:bank)"#).unwrap();
+        assert!(query.mentions_label("tax 2023"));
+        assert!(query.mentions_label("Bank"));
+        assert!(!query.mentions_label("invoice"));
+        assert!(!parse("").unwrap().mentions_label("tax"));
+    }
+}
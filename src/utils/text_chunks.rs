@@ -0,0 +1,38 @@
+//! Cutting long texts into pieces for APIs that take a limited amount at a time
+
+/// Pieces of at most `max_chars` characters without surrounding blanks, cut after a
+/// line where possible, else after a word
+pub fn split_text_chunks(text: &str, max_chars: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let limit = rest.char_indices().nth(max_chars).map_or(rest.len(), |(i, _)| i);
+        let end = if limit == rest.len() {
+            limit
+        } else {
+            let window = &rest[..limit];
+            window
+                .rfind('\n')
+                .or_else(|| window.rfind(char::is_whitespace))
+                .filter(|&i| i > 0)
+                .unwrap_or(limit)
+        };
+        chunks.push(rest[..end].trim_end());
+        rest = rest[end..].trim_start();
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_text_chunks_prefers_line_ends() {
+        assert_eq!(split_text_chunks("one two\nthree four", 12), vec!["one two", "three four"]);
+        assert_eq!(split_text_chunks("one two three", 9), vec!["one two", "three"]);
+        assert_eq!(split_text_chunks("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(split_text_chunks("  \n ", 10), Vec::<&str>::new());
+        assert_eq!(split_text_chunks("déjà vu", 5), vec!["déjà", "vu"]);
+    }
+}
@@ -0,0 +1,113 @@
+//! Accent and case folding that mirrors the readur_* text search configurations,
+//! so snippets highlight the same matches the database found
+
+/// Appends the folded form of `c`: lowercase with diacritics removed. With
+/// `expand_umlauts`, ä/ö/ü are spelled ae/oe/ue the way German does.
+fn push_folded(c: char, expand_umlauts: bool, out: &mut String) {
+    for lower in c.to_lowercase() {
+        let folded: &str = match lower {
+            'ä' if expand_umlauts => "ae",
+            'ö' if expand_umlauts => "oe",
+            'ü' if expand_umlauts => "ue",
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+            'æ' => "ae",
+            'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+            'ď' | 'đ' | 'ð' => "d",
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+            'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+            'ĥ' | 'ħ' => "h",
+            'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+            'ĵ' => "j",
+            'ķ' => "k",
+            'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+            'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+            'œ' => "oe",
+            'ŕ' | 'ŗ' | 'ř' => "r",
+            'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+            'ß' => "ss",
+            'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+            'þ' => "th",
+            'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+            'ŵ' => "w",
+            'ý' | 'ÿ' | 'ŷ' => "y",
+            'ź' | 'ż' | 'ž' => "z",
+            // Combining diacritical marks, as found in decomposed text
+            '\u{0300}'..='\u{036f}' => "",
+            other => {
+                out.push(other);
+                continue;
+            }
+        };
+        out.push_str(folded);
+    }
+}
+
+/// Folds a string, e.g. a search term
+pub fn fold(text: &str, expand_umlauts: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        push_folded(c, expand_umlauts, &mut out);
+    }
+    out
+}
+
+/// Folded copy of a text that remembers where each folded byte came from
+pub struct FoldedText {
+    pub text: String,
+    /// Byte range in the original text of the character each folded byte came from
+    origins: Vec<(usize, usize)>,
+}
+
+impl FoldedText {
+    pub fn new(original: &str, expand_umlauts: bool) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut origins = Vec::with_capacity(original.len());
+
+        for (start, c) in original.char_indices() {
+            let before = text.len();
+            push_folded(c, expand_umlauts, &mut text);
+            for _ in before..text.len() {
+                origins.push((start, start + c.len_utf8()));
+            }
+        }
+
+        Self { text, origins }
+    }
+
+    /// Byte range in the original text covering the folded range `start..end`
+    pub fn original_range(&self, start: usize, end: usize) -> (usize, usize) {
+        debug_assert!(start < end && end <= self.origins.len());
+        (self.origins[start].0, self.origins[end - 1].1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_accents_and_case() {
+        assert_eq!(fold("Résumé", false), "resume");
+        assert_eq!(fold("Straße", false), "strasse");
+        assert_eq!(fold("Müller", false), "muller");
+        assert_eq!(fold("Müller", true), "mueller");
+        assert_eq!(fold("Re\u{0301}sume\u{0301}", false), "resume");
+        assert_eq!(fold("ŒUVRE", false), "oeuvre");
+    }
+
+    #[test]
+    fn test_folded_text_maps_back_to_original() {
+        let original = "Lebenslauf von Jürgen Müller";
+        let folded = FoldedText::new(original, true);
+        assert_eq!(folded.text, "lebenslauf von juergen mueller");
+
+        let start = folded.text.find("mueller").unwrap();
+        let (from, to) = folded.original_range(start, start + "mueller".len());
+        assert_eq!(&original[from..to], "Müller");
+
+        let start = folded.text.find("jue").unwrap();
+        let (from, to) = folded.original_range(start, start + "jue".len());
+        assert_eq!(&original[from..to], "Jü");
+    }
+}
@@ -0,0 +1,285 @@
+//! ZIP archives written front to back, for streaming them to a client.
+//!
+//! `zip::ZipWriter` seeks back to fill in each entry's header, so it needs the whole
+//! archive at hand. Here entries are stored uncompressed with their CRC and size in a
+//! data descriptor after the data, and every byte is handed out as soon as it is known.
+//! Archives past 4 GiB or 65535 entries get ZIP64 records; a single entry must stay
+//! below 4 GiB.
+
+use bytes::{BufMut, Bytes, BytesMut};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use std::collections::HashSet;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const ZIP64_END_SIGNATURE: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const END_SIGNATURE: u32 = 0x0605_4b50;
+
+/// Sizes follow in a data descriptor; names are UTF-8
+const FLAGS: u16 = 0x0008 | 0x0800;
+const VERSION: u16 = 20;
+const VERSION_ZIP64: u16 = 45;
+const METHOD_STORED: u16 = 0;
+
+struct Entry {
+    name: Vec<u8>,
+    time: u16,
+    date: u16,
+    crc: u32,
+    size: u32,
+    offset: u64,
+}
+
+struct OpenEntry {
+    entry: Entry,
+    hasher: crc32fast::Hasher,
+    size: u64,
+}
+
+/// Encodes an archive into the bytes to send, in order
+#[derive(Default)]
+pub struct ZipStreamWriter {
+    offset: u64,
+    entries: Vec<Entry>,
+    current: Option<OpenEntry>,
+}
+
+impl ZipStreamWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Begin an entry; its data follows through `write`. Returns the local header.
+    pub fn start_entry(&mut self, name: &str, modified: DateTime<Utc>) -> Bytes {
+        let (time, date) = dos_date_time(modified);
+        let name = name.as_bytes().to_vec();
+
+        let mut header = BytesMut::with_capacity(30 + name.len());
+        header.put_u32_le(LOCAL_HEADER_SIGNATURE);
+        header.put_u16_le(VERSION);
+        header.put_u16_le(FLAGS);
+        header.put_u16_le(METHOD_STORED);
+        header.put_u16_le(time);
+        header.put_u16_le(date);
+        // CRC and sizes are in the data descriptor
+        header.put_u32_le(0);
+        header.put_u32_le(0);
+        header.put_u32_le(0);
+        header.put_u16_le(name.len() as u16);
+        header.put_u16_le(0);
+        header.put_slice(&name);
+
+        self.current = Some(OpenEntry {
+            entry: Entry { name, time, date, crc: 0, size: 0, offset: self.offset },
+            hasher: crc32fast::Hasher::new(),
+            size: 0,
+        });
+        self.offset += header.len() as u64;
+        header.freeze()
+    }
+
+    /// Data of the current entry; returned as is
+    pub fn write(&mut self, data: Bytes) -> Bytes {
+        if let Some(current) = self.current.as_mut() {
+            current.hasher.update(&data);
+            current.size += data.len() as u64;
+        }
+        self.offset += data.len() as u64;
+        data
+    }
+
+    /// End the current entry. Returns its data descriptor; Err when the entry
+    /// reached 4 GiB, which leaves the archive unusable.
+    pub fn finish_entry(&mut self) -> std::io::Result<Bytes> {
+        let Some(OpenEntry { mut entry, hasher, size }) = self.current.take() else {
+            return Ok(Bytes::new());
+        };
+        entry.size = u32::try_from(size)
+            .ok()
+            .filter(|size| *size != u32::MAX)
+            .ok_or_else(|| std::io::Error::other("ZIP entries must be smaller than 4 GiB"))?;
+        entry.crc = hasher.finalize();
+
+        let mut descriptor = BytesMut::with_capacity(16);
+        descriptor.put_u32_le(DATA_DESCRIPTOR_SIGNATURE);
+        descriptor.put_u32_le(entry.crc);
+        descriptor.put_u32_le(entry.size);
+        descriptor.put_u32_le(entry.size);
+
+        self.entries.push(entry);
+        self.offset += descriptor.len() as u64;
+        Ok(descriptor.freeze())
+    }
+
+    /// A whole entry whose data is already in memory
+    pub fn add_entry(&mut self, name: &str, modified: DateTime<Utc>, data: Bytes) -> std::io::Result<Bytes> {
+        let mut out = BytesMut::from(&self.start_entry(name, modified)[..]);
+        out.put(self.write(data));
+        out.put(self.finish_entry()?);
+        Ok(out.freeze())
+    }
+
+    /// The central directory and end records, after the last entry
+    pub fn finish(mut self) -> std::io::Result<Bytes> {
+        let mut out = BytesMut::new();
+        if self.current.is_some() {
+            out.put(self.finish_entry()?);
+        }
+
+        let directory_offset = self.offset;
+        for entry in &self.entries {
+            let zip64 = entry.offset >= u32::MAX as u64;
+            out.put_u32_le(CENTRAL_HEADER_SIGNATURE);
+            out.put_u16_le(VERSION_ZIP64);
+            out.put_u16_le(if zip64 { VERSION_ZIP64 } else { VERSION });
+            out.put_u16_le(FLAGS);
+            out.put_u16_le(METHOD_STORED);
+            out.put_u16_le(entry.time);
+            out.put_u16_le(entry.date);
+            out.put_u32_le(entry.crc);
+            out.put_u32_le(entry.size);
+            out.put_u32_le(entry.size);
+            out.put_u16_le(entry.name.len() as u16);
+            out.put_u16_le(if zip64 { 12 } else { 0 });
+            // Comment length, disk, internal and external attributes
+            out.put_u16_le(0);
+            out.put_u16_le(0);
+            out.put_u16_le(0);
+            out.put_u32_le(0);
+            out.put_u32_le(if zip64 { u32::MAX } else { entry.offset as u32 });
+            out.put_slice(&entry.name);
+            if zip64 {
+                out.put_u16_le(0x0001);
+                out.put_u16_le(8);
+                out.put_u64_le(entry.offset);
+            }
+        }
+        let directory_size = self.offset + out.len() as u64 - directory_offset;
+        let end_offset = directory_offset + directory_size;
+        let count = self.entries.len() as u64;
+
+        let zip64 = count >= u16::MAX as u64
+            || directory_offset >= u32::MAX as u64
+            || directory_size >= u32::MAX as u64;
+        if zip64 {
+            out.put_u32_le(ZIP64_END_SIGNATURE);
+            out.put_u64_le(44);
+            out.put_u16_le(VERSION_ZIP64);
+            out.put_u16_le(VERSION_ZIP64);
+            out.put_u32_le(0);
+            out.put_u32_le(0);
+            out.put_u64_le(count);
+            out.put_u64_le(count);
+            out.put_u64_le(directory_size);
+            out.put_u64_le(directory_offset);
+
+            out.put_u32_le(ZIP64_LOCATOR_SIGNATURE);
+            out.put_u32_le(0);
+            out.put_u64_le(end_offset);
+            out.put_u32_le(1);
+        }
+
+        out.put_u32_le(END_SIGNATURE);
+        out.put_u16_le(0);
+        out.put_u16_le(0);
+        let count16 = if zip64 { u16::MAX } else { count as u16 };
+        out.put_u16_le(count16);
+        out.put_u16_le(count16);
+        out.put_u32_le(if zip64 { u32::MAX } else { directory_size as u32 });
+        out.put_u32_le(if zip64 { u32::MAX } else { directory_offset as u32 });
+        out.put_u16_le(0);
+
+        Ok(out.freeze())
+    }
+}
+
+/// Entry names that are unique within an archive: `scan.pdf`, `scan (2).pdf`, ...
+#[derive(Default)]
+pub struct EntryNames {
+    used: HashSet<String>,
+}
+
+impl EntryNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `name` without path separators, numbered when already taken
+    pub fn unique(&mut self, name: &str) -> String {
+        let name = name.replace(['/', '\\'], "_");
+        let name = if name.trim().is_empty() { "document".to_string() } else { name };
+        if self.used.insert(name.to_lowercase()) {
+            return name;
+        }
+
+        let (stem, extension) = match name.rfind('.') {
+            Some(dot) if dot > 0 => name.split_at(dot),
+            _ => (name.as_str(), ""),
+        };
+        let mut number = 2;
+        loop {
+            let candidate = format!("{} ({}){}", stem, number, extension);
+            if self.used.insert(candidate.to_lowercase()) {
+                return candidate;
+            }
+            number += 1;
+        }
+    }
+}
+
+/// MS-DOS time and date; DOS dates start in 1980
+fn dos_date_time(time: DateTime<Utc>) -> (u16, u16) {
+    if time.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let dos_time = (time.hour() << 11) | (time.minute() << 5) | (time.second() / 2);
+    let dos_date = (((time.year() - 1980).min(127) as u32) << 9) | (time.month() << 5) | time.day();
+    (dos_time as u16, dos_date as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::io::Read;
+
+    #[test]
+    fn test_archive_is_readable() {
+        let modified = Utc.with_ymd_and_hms(2025, 3, 14, 15, 9, 26).unwrap();
+        let mut writer = ZipStreamWriter::new();
+        let mut archive = Vec::new();
+        archive.extend_from_slice(&writer.start_entry("scan.pdf", modified));
+        archive.extend_from_slice(&writer.write(Bytes::from_static(b"%PDF-1.7 ")));
+        archive.extend_from_slice(&writer.write(Bytes::from_static(b"content")));
+        archive.extend_from_slice(&writer.finish_entry().unwrap());
+        archive.extend_from_slice(&writer.add_entry("scan.pdf.txt", modified, Bytes::from_static(b"OCR text")).unwrap());
+        archive.extend_from_slice(&writer.finish().unwrap());
+
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        assert_eq!(zip.len(), 2);
+
+        let mut content = String::new();
+        zip.by_name("scan.pdf").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "%PDF-1.7 content");
+
+        let mut sidecar = zip.by_name("scan.pdf.txt").unwrap();
+        assert_eq!(sidecar.last_modified().year(), 2025);
+        assert_eq!(sidecar.last_modified().minute(), 9);
+        let mut text = String::new();
+        sidecar.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "OCR text");
+    }
+
+    #[test]
+    fn test_unique_names() {
+        let mut names = EntryNames::new();
+        assert_eq!(names.unique("scan.pdf"), "scan.pdf");
+        assert_eq!(names.unique("Scan.pdf"), "Scan (2).pdf");
+        assert_eq!(names.unique("scan.pdf"), "scan (3).pdf");
+        assert_eq!(names.unique("invoices/march"), "invoices_march");
+        assert_eq!(names.unique(".env"), ".env");
+        assert_eq!(names.unique(".env"), ".env (2)");
+    }
+}
@@ -74,6 +74,12 @@ fn create_interrupted_source(user_id: Uuid, source_type: SourceType) -> Source {
                 "file_extensions": [".pdf", ".docx"]
             });
         }
+        SourceType::Imap | SourceType::Dropbox | SourceType::OneDrive | SourceType::Sftp => {
+            config = json!({
+                "auto_sync": true,
+                "sync_interval_minutes": 60
+            });
+        }
     }
 
     Source {
@@ -206,6 +212,9 @@ fn should_auto_resume_sync(source: &Source) -> bool {
                 config.auto_sync
             } else { false }
         }
+        SourceType::Imap | SourceType::Dropbox | SourceType::OneDrive | SourceType::Sftp => {
+            source.config.get("auto_sync").and_then(|v| v.as_bool()).unwrap_or(false)
+        }
     }
 }
 
@@ -393,6 +402,7 @@ fn get_resume_priority(source: &Source) -> u32 {
         SourceType::LocalFolder => 1, // Highest priority (fastest)
         SourceType::WebDAV => 2,      // Medium priority
         SourceType::S3 => 3,          // Lower priority (potential costs)
+        SourceType::Imap | SourceType::Dropbox | SourceType::OneDrive | SourceType::Sftp => 3,
     };
     
     // Consider how long ago the sync was interrupted
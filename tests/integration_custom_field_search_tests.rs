@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use readur::db::Database;
+    use readur::models::{CreateUser, SearchRequest, UserRole};
+    use readur::test_utils::{document_helpers::create_test_document_with_hash, TestAuthHelper, TestContext};
+    use serde_json::{json, Value};
+    use tower::util::ServiceExt;
+    use uuid::Uuid;
+
+    fn search_request(query: &str) -> SearchRequest {
+        SearchRequest {
+            query: query.to_string(),
+            tags: None,
+            mime_types: None,
+            limit: Some(50),
+            offset: Some(0),
+            include_snippets: Some(false),
+            snippet_length: None,
+            search_mode: None,
+            near: None,
+            bbox: None,
+        }
+    }
+
+    async fn create_user(db: &Database) -> Uuid {
+        let suffix = Uuid::new_v4().simple().to_string();
+        db.create_user(CreateUser {
+            username: format!("fields_{}", suffix),
+            email: format!("fields_{}@example.com", suffix),
+            password: "password123".to_string(),
+            role: Some(UserRole::User),
+        })
+        .await
+        .unwrap()
+        .id
+    }
+
+    /// A document of the user's "Invoice" type with these custom field values
+    async fn create_invoice(db: &Database, user_id: Uuid, document_type_id: Uuid, fields: Value) -> Result<Uuid> {
+        let document = create_test_document_with_hash(user_id, "invoice.pdf", Uuid::new_v4().simple().to_string());
+        let document = db.create_document(document).await?;
+        db.set_document_custom_fields(document.id, document_type_id, fields.as_object().unwrap()).await?;
+        Ok(document.id)
+    }
+
+    async fn search(db: &Database, user_id: Uuid, query: &str) -> Result<Vec<Uuid>> {
+        let mut ids = db.search_document_ids(user_id, UserRole::User, &search_request(query), 50).await?;
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn sorted(mut ids: Vec<Uuid>) -> Vec<Uuid> {
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_custom_field_operators() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let db = &ctx.state.db;
+            let user_id = create_user(db).await;
+            let invoice_type = db.create_document_type(user_id, "Invoice", &[]).await?.id;
+
+            let acme = create_invoice(db, user_id, invoice_type, json!({
+                "total": 120.5,
+                "invoice_number": "INV-2024-007",
+                "due_date": "2024-02-15",
+            })).await?;
+            let globex = create_invoice(db, user_id, invoice_type, json!({ "total": 300, "invoice_number": 10042 })).await?;
+            let typed_in = create_invoice(db, user_id, invoice_type, json!({ "total": "lots", "invoice_number": "10042" })).await?;
+            db.create_document(create_test_document_with_hash(user_id, "notes.pdf", Uuid::new_v4().simple().to_string())).await?;
+
+            // Numeric ranges, open at either end; values that are not numbers never match
+            assert_eq!(search(db, user_id, "field.total:100..250").await?, [acme]);
+            assert_eq!(search(db, user_id, "field.total:100..").await?, sorted(vec![acme, globex]));
+            assert_eq!(search(db, user_id, "field.total:..120.5").await?, [acme]);
+            assert_eq!(search(db, user_id, "field.total:>200").await?, [globex]);
+            assert!(search(db, user_id, "field.total:500..900").await?.is_empty());
+
+            // Exact matches, with numbers stored either as numbers or as text
+            assert_eq!(search(db, user_id, "field.invoice_number:=INV-2024-007").await?, [acme]);
+            assert!(search(db, user_id, "field.invoice_number:=INV-2024").await?.is_empty());
+            assert_eq!(search(db, user_id, "field.invoice_number:10042").await?, sorted(vec![globex, typed_in]));
+            assert_eq!(search(db, user_id, r#"field.invoice_number:="10042""#).await?, sorted(vec![globex, typed_in]));
+
+            assert_eq!(search(db, user_id, "field.due_date:2024-02").await?, [acme]);
+            assert_eq!(search(db, user_id, "field.total:100.. -field.invoice_number:10042").await?, [acme]);
+
+            // Other users' documents with the same values stay out
+            let other_user_id = create_user(db).await;
+            let other_type = db.create_document_type(other_user_id, "Invoice", &[]).await?.id;
+            create_invoice(db, other_user_id, other_type, json!({ "total": 150 })).await?;
+            assert_eq!(search(db, user_id, "field.total:100..250").await?, [acme]);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invalid_custom_field_conditions_are_rejected() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let user = auth_helper.create_test_user().await;
+            let token = auth_helper.login_user(&user.username, &user.password).await;
+
+            assert!(ctx.state.db.search_document_ids(user.user_response.id, UserRole::User, &search_request("field.total:="), 50).await.is_err());
+
+            for query in ["field.total:%3D", "field.total:%3Elots"] {
+                let response = ctx.app.clone()
+                    .oneshot(
+                        axum::http::Request::builder()
+                            .method("GET")
+                            .uri(format!("/api/search/enhanced?query={}", query))
+                            .header("Authorization", format!("Bearer {}", token))
+                            .body(axum::body::Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+            }
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+}
@@ -77,6 +77,8 @@ mod tests {
                         highlight_ranges.push(HighlightRange {
                             start: match_start as i32,
                             end: (match_start + query.len()) as i32,
+                            start_char: snippet_text[..match_start].chars().count() as i32,
+                            end_char: (snippet_text[..match_start].chars().count() + query.chars().count()) as i32,
                         });
                     }
 
@@ -84,6 +86,10 @@ mod tests {
                         text: snippet_text.to_string(),
                         start_offset: safe_start as i32,
                         end_offset: safe_end as i32,
+                        start_char: full_text[..safe_start].chars().count() as i32,
+                        end_char: full_text[..safe_end].chars().count() as i32,
+                        source: "content".to_string(),
+                        page_number: None,
                         highlight_ranges,
                     });
 
@@ -214,6 +220,8 @@ mod tests {
             include_snippets: None,
             snippet_length: None,
             search_mode: None,
+            near: None,
+            bbox: None,
         };
         
         // Test that default values work correctly
@@ -233,6 +241,8 @@ mod tests {
             include_snippets: Some(true),
             snippet_length: Some(300),
             search_mode: Some(SearchMode::Phrase),
+            near: None,
+            bbox: None,
         };
         
         assert_eq!(request.query, "test query");
@@ -268,6 +278,8 @@ mod tests {
         let range = HighlightRange {
             start: 10,
             end: 20,
+            start_char: 10,
+            end_char: 20,
         };
         
         assert_eq!(range.start, 10);
@@ -285,8 +297,12 @@ mod tests {
                 text: "This is a test snippet".to_string(),
                 start_offset: 0,
                 end_offset: 22,
+                start_char: 0,
+                end_char: 22,
+                source: "content".to_string(),
+                page_number: None,
                 highlight_ranges: vec![
-                    HighlightRange { start: 10, end: 14 }
+                    HighlightRange { start: 10, end: 14, start_char: 10, end_char: 14 }
                 ],
             }
         ];
@@ -306,6 +322,7 @@ mod tests {
             ocr_status: Some("completed".to_string()),
             search_rank: Some(0.75),
             snippets,
+            matched_fields: Vec::new(),
         };
         
         assert_eq!(response.id, doc_id);
@@ -600,6 +617,8 @@ mod tests {
             include_snippets: None,
             snippet_length: None,
             search_mode: None,
+            near: None,
+            bbox: None,
         };
         
         // Should handle empty query gracefully
@@ -615,6 +634,8 @@ mod tests {
             include_snippets: Some(true),
             snippet_length: Some(i32::MAX),
             search_mode: Some(SearchMode::Boolean),
+            near: None,
+            bbox: None,
         };
         
         // Should handle extreme values without panicking
@@ -693,11 +714,16 @@ mod tests {
                     text: "Test snippet".to_string(),
                     start_offset: 0,
                     end_offset: 12,
+                    start_char: 0,
+                    end_char: 12,
+                    source: "ocr_text".to_string(),
+                    page_number: Some(1),
                     highlight_ranges: vec![
-                        HighlightRange { start: 0, end: 4 }
+                        HighlightRange { start: 0, end: 4, start_char: 0, end_char: 4 }
                     ],
                 }
             ],
+            matched_fields: Vec::new(),
         };
         
         // Test that all fields are properly accessible
@@ -953,6 +979,8 @@ mod tests {
             include_snippets: Some(true),
             snippet_length: Some(100),
             search_mode: Some(SearchMode::Simple),
+            near: None,
+            bbox: None,
         };
         
         let result = ctx.state.db.enhanced_search_documents(user.user_response.id, &search_request).await;
@@ -422,6 +422,9 @@ impl FileProcessingTestClient {
                                 file_owner: doc.file_owner.clone(),
                                 file_group: doc.file_group.clone(),
                                 source_metadata: doc.source_metadata.clone(),
+                                relations: doc.relations.clone(),
+                                suggested_relations: doc.suggested_relations.clone(),
+                                processing_costs: doc.processing_costs.clone(),
                             };
                             return Ok(doc_copy);
                         }
@@ -0,0 +1,176 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use readur::db::Database;
+    use readur::models::{CreateUser, Document, SearchMode, SearchRequest, UserRole};
+    use readur::test_utils::{document_helpers::create_test_document_with_hash, TestAuthHelper, TestContext};
+    use serde_json::Value;
+    use tower::util::ServiceExt;
+    use uuid::Uuid;
+
+    fn fuzzy_request(query: &str) -> SearchRequest {
+        SearchRequest {
+            query: query.to_string(),
+            tags: None,
+            mime_types: None,
+            limit: Some(50),
+            offset: Some(0),
+            include_snippets: Some(false),
+            snippet_length: None,
+            search_mode: Some(SearchMode::Fuzzy),
+            near: None,
+            bbox: None,
+        }
+    }
+
+    async fn create_user(db: &Database) -> Uuid {
+        let suffix = Uuid::new_v4().simple().to_string();
+        db.create_user(CreateUser {
+            username: format!("fuzzy_{}", suffix),
+            email: format!("fuzzy_{}@example.com", suffix),
+            password: "password123".to_string(),
+            role: Some(UserRole::User),
+        })
+        .await
+        .unwrap()
+        .id
+    }
+
+    fn document(user_id: Uuid, content: &str) -> Document {
+        let mut document = create_test_document_with_hash(user_id, "document.pdf", Uuid::new_v4().simple().to_string());
+        document.content = Some(content.to_string());
+        document.ocr_text = None;
+        document
+    }
+
+    async fn set_fuzzy_threshold(db: &Database, user_id: Uuid, threshold: f32) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO settings (user_id, fuzzy_search_threshold) VALUES ($1, $2)
+               ON CONFLICT (user_id) DO UPDATE SET fuzzy_search_threshold = EXCLUDED.fuzzy_search_threshold"#,
+        )
+        .bind(user_id)
+        .bind(threshold)
+        .execute(&db.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn fuzzy_search(db: &Database, user_id: Uuid, query: &str) -> Result<Vec<Uuid>> {
+        db.search_document_ids(user_id, UserRole::User, &fuzzy_request(query), 50).await
+    }
+
+    #[tokio::test]
+    async fn test_fuzzy_search_tolerates_typos() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let db = &ctx.state.db;
+            let user_id = create_user(db).await;
+            let invoice = db.create_document(document(user_id, "Invoice from ACME for consulting services")).await?;
+            db.create_document(document(user_id, "Grocery list: bread, milk, inventory of the pantry")).await?;
+
+            // A dropped letter, an extra one and an OCR misreading
+            for typo in ["invoce", "invoicee", "lnvoice"] {
+                assert_eq!(fuzzy_search(db, user_id, typo).await?, [invoice.id], "{}", typo);
+            }
+            assert!(fuzzy_search(db, user_id, "contarct").await?.is_empty());
+
+            // Other users' documents stay out of reach
+            let other_user_id = create_user(db).await;
+            assert!(fuzzy_search(db, other_user_id, "invoce").await?.is_empty());
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fuzzy_search_uses_the_users_threshold() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let db = &ctx.state.db;
+            let user_id = create_user(db).await;
+            let invoice = db.create_document(document(user_id, "Invoice from ACME for consulting services")).await?;
+
+            // Swapped letters cost most trigrams of a short word
+            assert!(fuzzy_search(db, user_id, "invioce").await?.is_empty());
+            set_fuzzy_threshold(db, user_id, 0.35).await?;
+            assert_eq!(fuzzy_search(db, user_id, "invioce").await?, [invoice.id]);
+
+            set_fuzzy_threshold(db, user_id, 0.9).await?;
+            assert!(fuzzy_search(db, user_id, "invoce").await?.is_empty());
+            assert_eq!(fuzzy_search(db, user_id, "invoice").await?, [invoice.id]);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_enhanced_search_falls_back_to_fuzzy_matching() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let user = auth_helper.create_test_user().await;
+            let token = auth_helper.login_user(&user.username, &user.password).await;
+            let mut invoice = document(user.user_response.id, "Invoice from ACME for consulting services");
+            invoice.tags = vec!["finance".to_string()];
+            let invoice = ctx.state.db.create_document(invoice).await?;
+
+            let search = |uri: &'static str| {
+                let app = ctx.app.clone();
+                let token = token.clone();
+                async move {
+                    let response = app
+                        .oneshot(
+                            axum::http::Request::builder()
+                                .method("GET")
+                                .uri(uri)
+                                .header("Authorization", format!("Bearer {}", token))
+                                .body(axum::body::Body::empty())
+                                .unwrap(),
+                        )
+                        .await
+                        .unwrap();
+                    let status = response.status();
+                    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                    (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+                }
+            };
+
+            let (status, body) = search("/api/search/enhanced?query=invoce").await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["total"], 1);
+            assert_eq!(body["documents"][0]["id"], invoice.id.to_string());
+
+            // Queries with filters, and later pages, are answered as typed
+            let (status, body) = search("/api/search/enhanced?query=invoce%20tag:finance").await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["total"], 0);
+            let (status, body) = search("/api/search/enhanced?query=invoce&offset=25").await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["total"], 0);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+}
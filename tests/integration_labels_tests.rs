@@ -24,6 +24,7 @@ mod tests {
 
             let label_data = CreateLabel {
                 name: "Test Label".to_string(),
+                parent_id: None,
                 description: Some("A test label".to_string()),
                 color: "#ff0000".to_string(),
                 background_color: None,
@@ -50,7 +51,7 @@ mod tests {
 
             // Verify label was created
             let created_label = sqlx::query_as::<_, Label>(
-                "SELECT id, user_id, name, description, color, background_color, icon, is_system, created_at, updated_at, 0::bigint as document_count, 0::bigint as source_count FROM labels WHERE id = $1"
+                "SELECT id, user_id, parent_id, name, description, color, background_color, icon, is_system, created_at, updated_at, 0::bigint as document_count, 0::bigint as source_count FROM labels WHERE id = $1"
             )
             .bind(label_id)
             .fetch_one(&ctx.state.db.pool)
@@ -152,6 +153,7 @@ mod tests {
             // Update label
             let update_data = UpdateLabel {
                 name: Some("Updated Name".to_string()),
+                parent_id: None,
                 description: Some("Updated description".to_string()),
                 color: Some("#00ff00".to_string()),
                 background_color: None,
@@ -168,7 +170,7 @@ mod tests {
                     icon = COALESCE($5, icon),
                     updated_at = CURRENT_TIMESTAMP
                 WHERE id = $1 AND user_id = $6
-                RETURNING id, user_id, name, description, color, background_color, icon, is_system, created_at, updated_at, 0::bigint as document_count, 0::bigint as source_count
+                RETURNING id, user_id, parent_id, name, description, color, background_color, icon, is_system, created_at, updated_at, 0::bigint as document_count, 0::bigint as source_count
                 "#,
             )
             .bind(label_id)
@@ -969,4 +971,92 @@ mod tests {
 
         result.unwrap();
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_label_search_includes_nested_labels() {
+        let ctx = TestContext::new().await;
+
+        // Ensure cleanup happens even if test fails
+        let result: Result<()> = async {
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let user = auth_helper.create_test_user().await;
+            let user_id = user.user_response.id;
+
+            let document_id = Uuid::new_v4();
+            sqlx::query(
+                r#"
+                INSERT INTO documents (
+                    id, user_id, filename, original_filename, file_path,
+                    file_size, mime_type, created_at, updated_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW())
+                "#,
+            )
+            .bind(document_id)
+            .bind(user_id)
+            .bind("tax-return.pdf")
+            .bind("tax-return.pdf")
+            .bind("/test/tax-return.pdf")
+            .bind(1024)
+            .bind("application/pdf")
+            .execute(&ctx.state.db.pool)
+            .await
+            .expect("Failed to create test document");
+
+            // Finance > Taxes > 2024, with the document labeled 2024
+            let mut parent_id: Option<Uuid> = None;
+            for name in ["Finance", "Taxes", "2024"] {
+                let label_id = sqlx::query_scalar::<_, Uuid>(
+                    "INSERT INTO labels (user_id, name, parent_id) VALUES ($1, $2, $3) RETURNING id",
+                )
+                .bind(user_id)
+                .bind(format!("{} {}", name, user_id))
+                .bind(parent_id)
+                .fetch_one(&ctx.state.db.pool)
+                .await
+                .unwrap();
+                parent_id = Some(label_id);
+            }
+            ctx.state.db.assign_label(document_id, parent_id.unwrap(), user_id).await.unwrap();
+
+            let matches = |name: &str| {
+                let search_request = readur::models::SearchRequest {
+                    query: format!("label:\"{} {}\"", name, user_id),
+                    tags: None,
+                    mime_types: None,
+                    limit: None,
+                    offset: None,
+                    include_snippets: None,
+                    snippet_length: None,
+                    search_mode: None,
+                    near: None,
+                    bbox: None,
+                };
+                let db = ctx.state.db.clone();
+                async move { db.document_matches_search(document_id, user_id, &search_request).await.unwrap() }
+            };
+
+            assert!(matches("Finance").await);
+            assert!(matches("Taxes").await);
+            assert!(matches("2024").await);
+
+            // Moving the year out of the tree drops it from its former ancestors
+            sqlx::query("UPDATE labels SET parent_id = NULL WHERE id = $1")
+                .bind(parent_id)
+                .execute(&ctx.state.db.pool)
+                .await
+                .unwrap();
+            assert!(!matches("Finance").await);
+            assert!(matches("2024").await);
+
+            Ok(())
+        }.await;
+
+        // Always cleanup database connections and test data
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+}
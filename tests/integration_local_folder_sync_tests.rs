@@ -28,6 +28,7 @@ fn create_test_local_config() -> LocalFolderSourceConfig {
         watch_folders: vec!["/test/documents".to_string(), "/test/images".to_string()],
         recursive: true,
         follow_symlinks: false,
+        watch_mode: Default::default(),
         auto_sync: true,
         sync_interval_minutes: 30,
         file_extensions: vec![".pdf".to_string(), ".txt".to_string(), ".jpg".to_string()],
@@ -331,6 +332,7 @@ fn test_error_handling() {
         watch_folders: vec!["/this/path/does/not/exist".to_string()],
         recursive: true,
         follow_symlinks: false,
+        watch_mode: Default::default(),
         auto_sync: true,
         sync_interval_minutes: 30,
         file_extensions: vec![".txt".to_string()],
@@ -343,6 +345,7 @@ fn test_error_handling() {
         watch_folders: Vec::new(),
         recursive: true,
         follow_symlinks: false,
+        watch_mode: Default::default(),
         auto_sync: true,
         sync_interval_minutes: 30,
         file_extensions: vec![".txt".to_string()],
@@ -355,6 +358,7 @@ fn test_error_handling() {
         watch_folders: vec!["/test".to_string()],
         recursive: true,
         follow_symlinks: false,
+        watch_mode: Default::default(),
         auto_sync: true,
         sync_interval_minutes: 0, // Invalid
         file_extensions: vec![".txt".to_string()],
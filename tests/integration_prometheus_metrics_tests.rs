@@ -150,6 +150,10 @@ async fn test_all_expected_metrics_are_present() {
         "readur_ocr_completed_today",
         "readur_ocr_stuck_jobs",
         "readur_ocr_queue_depth",
+        "readur_ocr_worker_max_concurrent_jobs",
+        "readur_ocr_worker_active_jobs",
+        "readur_ocr_worker_jobs_completed_total",
+        "readur_ocr_worker_jobs_failed_total",
         
         // User metrics
         "readur_users_total",
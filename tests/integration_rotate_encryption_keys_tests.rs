@@ -0,0 +1,199 @@
+//! Runs the `rotate_encryption_keys` binary against a test database and checks that
+//! everything it touched opens with the new key versions
+
+use std::process::Command;
+use std::sync::Arc;
+
+use readur::{
+    db::Database,
+    models::{CreateSource, CreateUser, KeyOwner, SourceType, User, UserRole},
+    services::{encryption::{self, EncryptionService}, file_service::FileService},
+    storage::{factory::create_storage_backend, StorageConfig},
+    test_utils::{document_helpers::create_test_document, TestContext},
+};
+use tempfile::TempDir;
+use uuid::Uuid;
+
+/// base64 of 32 bytes, the format of `openssl rand -base64 32`
+const MASTER_KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+
+async fn encrypted_file_service(db: &Database, upload_dir: &TempDir) -> (FileService, Arc<EncryptionService>) {
+    std::env::set_var("ENCRYPTION_MASTER_KEY", MASTER_KEY);
+    let encryption = Arc::new(EncryptionService::from_env(db.clone()).unwrap().unwrap());
+
+    let upload_path = upload_dir.path().to_string_lossy().to_string();
+    let storage_backend = create_storage_backend(StorageConfig::Local { upload_path: upload_path.clone() })
+        .await
+        .unwrap();
+    let file_service = FileService::with_storage(upload_path, storage_backend).with_encryption(encryption.clone());
+    (file_service, encryption)
+}
+
+async fn create_user(db: &Database) -> User {
+    db.create_user(CreateUser {
+        username: format!("rotation_{}", Uuid::new_v4().simple()),
+        email: format!("rotation_{}@example.com", Uuid::new_v4().simple()),
+        password: "password123".to_string(),
+        role: Some(UserRole::User),
+    })
+    .await
+    .unwrap()
+}
+
+/// Store `data` as an encrypted document of `user_id` and return its id
+async fn create_document(db: &Database, file_service: &FileService, user_id: Uuid, filename: &str, data: &[u8]) -> Uuid {
+    let mut document = create_test_document(user_id);
+    document.filename = filename.to_string();
+    document.original_filename = filename.to_string();
+    document.file_path = file_service.save_document_file(user_id, document.id, filename, data).await.unwrap();
+    document.file_hash = Some(Uuid::new_v4().to_string());
+    db.create_document(document).await.unwrap().id
+}
+
+async fn document_file(db: &Database, document_id: Uuid) -> (String, Vec<u8>) {
+    let file_path: String = sqlx::query_scalar("SELECT file_path FROM documents WHERE id = $1")
+        .bind(document_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    let stored = std::fs::read(&file_path).unwrap();
+    (file_path, stored)
+}
+
+fn run_rotation(ctx: &TestContext, upload_dir: &TempDir, args: &[&str]) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rotate_encryption_keys"))
+        .args(args)
+        .env("DATABASE_URL", &ctx.state.config.database_url)
+        .env("UPLOAD_PATH", upload_dir.path())
+        .env("ENCRYPTION_MASTER_KEY", MASTER_KEY)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "rotate_encryption_keys {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[tokio::test]
+async fn test_rotation_reencrypts_documents_and_source_secrets() {
+    let ctx = TestContext::new().await;
+    let db = &ctx.state.db;
+    let upload_dir = TempDir::new().unwrap();
+    let (file_service, encryption) = encrypted_file_service(db, &upload_dir).await;
+    // Sources seal their credentials through the installed service; this is the only
+    // test of the file that installs one, as it is bound to this test's database
+    encryption::install(encryption.clone());
+
+    let user = create_user(db).await;
+    let owner = KeyOwner::User(user.id);
+    let first_key = encryption.active_key(owner).await.unwrap();
+
+    let invoice = create_document(db, &file_service, user.id, "invoice.pdf", b"%PDF-1.7 invoice").await;
+    let receipt = create_document(db, &file_service, user.id, "receipt.txt", b"receipt for march").await;
+    let source = db
+        .create_source(user.id, &CreateSource {
+            name: "Nextcloud".to_string(),
+            source_type: SourceType::WebDAV,
+            enabled: Some(false),
+            config: serde_json::json!({
+                "server_url": "https://cloud.example.com",
+                "username": "alice",
+                "password": "hunter2",
+                "watch_folders": ["/Documents"],
+                "file_extensions": ["pdf"],
+                "auto_sync": false,
+                "sync_interval_minutes": 60,
+                "server_type": "nextcloud",
+            }),
+        })
+        .await
+        .unwrap();
+    for document_id in [invoice, receipt] {
+        let (_, stored) = document_file(db, document_id).await;
+        assert_eq!(encryption::sealed_key_id(&stored), Some(first_key.id));
+    }
+
+    run_rotation(&ctx, &upload_dir, &["--user", &user.username, "--reencrypt"]);
+
+    let keys = db.get_encryption_keys(owner).await.unwrap();
+    assert_eq!(keys.len(), 2);
+    let new_key = db.get_active_encryption_key(owner).await.unwrap().unwrap();
+    assert_eq!(new_key.version, first_key.version + 1);
+
+    for (document_id, plaintext) in [(invoice, &b"%PDF-1.7 invoice"[..]), (receipt, &b"receipt for march"[..])] {
+        let (file_path, stored) = document_file(db, document_id).await;
+        assert_eq!(encryption::sealed_key_id(&stored), Some(new_key.id));
+        assert_eq!(file_service.read_file(&file_path).await.unwrap(), plaintext);
+    }
+
+    let raw_config: serde_json::Value = sqlx::query_scalar("SELECT config FROM sources WHERE id = $1")
+        .bind(source.id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert!(encryption::secrets_sealed_with(&raw_config, new_key.id));
+    assert_ne!(raw_config["password"], "hunter2");
+    let opened = db.get_source(user.id, source.id).await.unwrap().unwrap();
+    assert_eq!(opened.config["password"], "hunter2");
+    assert_eq!(opened.config["username"], "alice");
+
+    let events = db.get_encryption_key_events(owner, 10).await.unwrap();
+    let reencrypted = events.iter().find(|event| event.action == "reencrypted").unwrap();
+    assert_eq!(reencrypted.detail.as_deref(), Some("3 files and sources re-encrypted, 0 failed"));
+}
+
+#[tokio::test]
+async fn test_reencrypt_only_resumes_an_interrupted_run() {
+    let ctx = TestContext::new().await;
+    let db = &ctx.state.db;
+    let upload_dir = TempDir::new().unwrap();
+    let (file_service, encryption) = encrypted_file_service(db, &upload_dir).await;
+
+    let user = create_user(db).await;
+    let owner = KeyOwner::User(user.id);
+    let first_key = encryption.active_key(owner).await.unwrap();
+    let documents = [
+        (create_document(db, &file_service, user.id, "a.txt", b"first").await, &b"first"[..]),
+        (create_document(db, &file_service, user.id, "b.txt", b"second").await, &b"second"[..]),
+        (create_document(db, &file_service, user.id, "c.txt", b"third").await, &b"third"[..]),
+    ];
+
+    // A run that rotated and got through the first document before it was stopped
+    let new_key = encryption.rotate(owner, None, None).await.unwrap();
+    let (done, _) = documents[0];
+    let (old_path, _) = document_file(db, done).await;
+    let new_path = file_service
+        .reencrypt_document_file(user.id, done, "a.txt", &old_path)
+        .await
+        .unwrap()
+        .unwrap();
+    db.set_document_file_path(done, &new_path).await.unwrap();
+    let (_, pending) = document_file(db, documents[1].0).await;
+    assert_eq!(encryption::sealed_key_id(&pending), Some(first_key.id));
+
+    run_rotation(&ctx, &upload_dir, &["--user", &user.username, "--reencrypt-only"]);
+
+    // No further rotation, and the finished document is left where it is
+    let active = db.get_active_encryption_key(owner).await.unwrap().unwrap();
+    assert_eq!(active.id, new_key.id);
+    assert_eq!(db.get_encryption_keys(owner).await.unwrap().len(), 2);
+    assert_eq!(document_file(db, done).await.0, new_path);
+
+    for (document_id, plaintext) in documents {
+        let (file_path, stored) = document_file(db, document_id).await;
+        assert_eq!(encryption::sealed_key_id(&stored), Some(new_key.id));
+        assert_eq!(file_service.read_file(&file_path).await.unwrap(), plaintext);
+    }
+
+    let events = db.get_encryption_key_events(owner, 10).await.unwrap();
+    let reencrypted = events.iter().find(|event| event.action == "reencrypted").unwrap();
+    assert_eq!(reencrypted.detail.as_deref(), Some("2 files and sources re-encrypted, 0 failed"));
+
+    // Running it again finds nothing left to do
+    run_rotation(&ctx, &upload_dir, &["--user", &user.username, "--reencrypt-only"]);
+    let events = db.get_encryption_key_events(owner, 10).await.unwrap();
+    let latest = events.iter().find(|event| event.action == "reencrypted").unwrap();
+    assert_eq!(latest.detail.as_deref(), Some("0 files and sources re-encrypted, 0 failed"));
+}
@@ -32,6 +32,7 @@ fn create_test_aws_s3_config() -> S3SourceConfig {
         watch_folders: vec!["documents/".to_string()],
         auto_sync: true,
         sync_interval_minutes: 120,
+        notifications: None,
         file_extensions: vec![".pdf".to_string(), ".txt".to_string(), ".docx".to_string()],
     }
 }
@@ -48,6 +49,7 @@ fn create_test_minio_config() -> S3SourceConfig {
         watch_folders: vec!["".to_string()],
         auto_sync: true,
         sync_interval_minutes: 60,
+        notifications: None,
         file_extensions: vec![".pdf".to_string(), ".jpg".to_string()],
     }
 }
@@ -367,6 +369,7 @@ fn test_s3_error_handling_scenarios() {
         watch_folders: vec!["".to_string()],
         auto_sync: true,
         sync_interval_minutes: 60,
+        notifications: None,
         file_extensions: vec![".pdf".to_string()],
     };
     
@@ -384,6 +387,7 @@ fn test_s3_error_handling_scenarios() {
         watch_folders: vec!["".to_string()],
         auto_sync: true,
         sync_interval_minutes: 60,
+        notifications: None,
         file_extensions: vec![".pdf".to_string()],
     };
     
@@ -401,6 +405,7 @@ fn test_s3_error_handling_scenarios() {
         watch_folders: vec!["".to_string()],
         auto_sync: true,
         sync_interval_minutes: 60,
+        notifications: None,
         file_extensions: vec![".pdf".to_string()],
     };
     
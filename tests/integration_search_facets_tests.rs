@@ -0,0 +1,188 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use chrono::{TimeZone, Utc};
+    use readur::db::Database;
+    use readur::models::{CreateSource, CreateUser, Document, FacetItem, SearchRequest, SourceType, UserRole};
+    use readur::test_utils::{document_helpers::create_test_document_with_hash, TestAuthHelper, TestContext};
+    use serde_json::Value;
+    use tower::util::ServiceExt;
+    use uuid::Uuid;
+
+    fn search_request(query: &str) -> SearchRequest {
+        SearchRequest {
+            query: query.to_string(),
+            tags: None,
+            mime_types: None,
+            limit: Some(50),
+            offset: Some(0),
+            include_snippets: Some(false),
+            snippet_length: None,
+            search_mode: None,
+            near: None,
+            bbox: None,
+        }
+    }
+
+    async fn create_user(db: &Database) -> Uuid {
+        let suffix = Uuid::new_v4().simple().to_string();
+        db.create_user(CreateUser {
+            username: format!("facets_{}", suffix),
+            email: format!("facets_{}@example.com", suffix),
+            password: "password123".to_string(),
+            role: Some(UserRole::User),
+        })
+        .await
+        .unwrap()
+        .id
+    }
+
+    fn document(user_id: Uuid, content: &str) -> Document {
+        let mut document = create_test_document_with_hash(user_id, "document.pdf", Uuid::new_v4().simple().to_string());
+        document.content = Some(content.to_string());
+        document.ocr_text = None;
+        document
+    }
+
+    fn items(facet: &[FacetItem]) -> Vec<(&str, i64)> {
+        facet.iter().map(|item| (item.value.as_str(), item.count)).collect()
+    }
+
+    /// Two invoices of `user_id` with a label, a source and a correspondent between them,
+    /// and a recipe that does not match `invoice`
+    async fn create_documents(db: &Database, user_id: Uuid) -> Result<()> {
+        let source = db
+            .create_source(user_id, &CreateSource {
+                name: "Nextcloud".to_string(),
+                source_type: SourceType::WebDAV,
+                enabled: Some(false),
+                config: serde_json::json!({ "server_url": "https://cloud.example.com" }),
+            })
+            .await?;
+
+        let mut paid = document(user_id, "Invoice from ACME for consulting");
+        paid.tags = vec!["finance".to_string()];
+        paid.source_type = Some("webdav".to_string());
+        paid.created_at = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let paid = db.create_document(paid).await?;
+
+        let mut scanned = document(user_id, "Second invoice, scanned");
+        scanned.mime_type = "image/png".to_string();
+        scanned.tags = vec!["finance".to_string(), "scan".to_string()];
+        scanned.source_type = Some("webdav".to_string());
+        scanned.source_id = Some(source.id);
+        scanned.created_at = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        db.create_document(scanned).await?;
+
+        let mut recipe = document(user_id, "Recipe for bread");
+        recipe.tags = vec!["cooking".to_string()];
+        recipe.created_at = Utc.with_ymd_and_hms(2023, 1, 1, 12, 0, 0).unwrap();
+        db.create_document(recipe).await?;
+
+        let label_id: Uuid = sqlx::query_scalar("INSERT INTO labels (user_id, name, color) VALUES ($1, 'Paid', '#00aa00') RETURNING id")
+            .bind(user_id)
+            .fetch_one(&db.pool)
+            .await?;
+        db.add_document_labels(paid.id, &[label_id], user_id).await?;
+
+        let correspondent_id = db.ensure_correspondent(user_id, "ACME").await?;
+        db.assign_correspondent(user_id, &[paid.id], Some(correspondent_id), "manual").await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_facets_count_all_matching_documents() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let db = &ctx.state.db;
+            let user_id = create_user(db).await;
+            create_documents(db, user_id).await?;
+
+            // Another user's invoice is not counted
+            let other_user_id = create_user(db).await;
+            let mut other = document(other_user_id, "Invoice of someone else");
+            other.tags = vec!["finance".to_string()];
+            db.create_document(other).await?;
+
+            let facets = db.search_result_facets(user_id, UserRole::User, &search_request("invoice")).await?;
+            assert_eq!(items(&facets.labels), [("finance", 2), ("Paid", 1), ("scan", 1)]);
+            assert_eq!(items(&facets.mime_types), [("application/pdf", 1), ("image/png", 1)]);
+            assert_eq!(items(&facets.sources), [("Nextcloud", 1), ("webdav", 1)]);
+            assert_eq!(items(&facets.correspondents), [("ACME", 1)]);
+            assert_eq!(items(&facets.years), [("2025", 1), ("2024", 1)]);
+
+            // Counts cover every match, not the requested page
+            let mut first_page = search_request("invoice");
+            first_page.limit = Some(1);
+            let facets = db.search_result_facets(user_id, UserRole::User, &first_page).await?;
+            assert_eq!(items(&facets.mime_types).len(), 2);
+
+            // Filters of the search narrow the counts too
+            let facets = db.search_result_facets(user_id, UserRole::User, &search_request("invoice tag:scan")).await?;
+            assert_eq!(items(&facets.years), [("2024", 1)]);
+            assert!(facets.correspondents.is_empty());
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_enhanced_search_returns_facets_on_request() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let user = auth_helper.create_test_user().await;
+            let token = auth_helper.login_user(&user.username, &user.password).await;
+            create_documents(&ctx.state.db, user.user_response.id).await?;
+
+            let search = |uri: &'static str| {
+                let app = ctx.app.clone();
+                let token = token.clone();
+                async move {
+                    let response = app
+                        .oneshot(
+                            axum::http::Request::builder()
+                                .method("GET")
+                                .uri(uri)
+                                .header("Authorization", format!("Bearer {}", token))
+                                .body(axum::body::Body::empty())
+                                .unwrap(),
+                        )
+                        .await
+                        .unwrap();
+                    let status = response.status();
+                    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                    (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+                }
+            };
+
+            let (status, body) = search("/api/search/enhanced?query=invoice").await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["total"], 2);
+            assert!(body.get("facets").is_none());
+
+            let (status, body) = search("/api/search/enhanced?query=invoice&limit=1&include_facets=true").await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["documents"].as_array().unwrap().len(), 1);
+            assert_eq!(body["facets"]["labels"][0], serde_json::json!({ "value": "finance", "count": 2 }));
+            assert_eq!(body["facets"]["years"].as_array().unwrap().len(), 2);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+}
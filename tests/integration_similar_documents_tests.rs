@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum::Router;
+    use readur::models::Document;
+    use readur::test_utils::{document_helpers::create_test_document_with_hash, TestAuthHelper, TestContext};
+    use serde_json::Value;
+    use tower::util::ServiceExt;
+    use uuid::Uuid;
+
+    fn document(user_id: Uuid, filename: &str, content: &str) -> Document {
+        let mut document = create_test_document_with_hash(user_id, filename, Uuid::new_v4().simple().to_string());
+        document.content = Some(content.to_string());
+        document.ocr_text = None;
+        document.tags = Vec::new();
+        document
+    }
+
+    async fn get(app: &Router, uri: &str, token: &str) -> (StatusCode, Value) {
+        let response = app
+            .clone()
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("GET")
+                    .uri(uri)
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn ids(response: &Value) -> Vec<String> {
+        response["documents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|document| document["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_similar_documents_are_ranked_by_shared_terms() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let db = &ctx.state.db;
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let user = auth_helper.create_test_user().await;
+            let token = auth_helper.login_user(&user.username, &user.password).await;
+            let user_id = user.user_response.id;
+
+            let contract = db.create_document(document(
+                user_id,
+                "contract.pdf",
+                "Service agreement between Initech and Globex covering maintenance, warranty and termination",
+            )).await?;
+            let amendment = db.create_document(document(
+                user_id,
+                "amendment.pdf",
+                "Amendment to the service agreement between Initech and Globex extending the warranty",
+            )).await?;
+            let invoice = db.create_document(document(
+                user_id,
+                "invoice.pdf",
+                "Invoice from Globex for consulting hours",
+            )).await?;
+            let recipe = db.create_document(document(user_id, "recipe.pdf", "Banana bread with flour and sugar")).await?;
+
+            let (status, body) = get(&ctx.app, &format!("/api/documents/{}/similar", contract.id), &token).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["document_id"], contract.id.to_string());
+            assert_eq!(ids(&body), [amendment.id.to_string(), invoice.id.to_string()]);
+            assert!(!ids(&body).contains(&recipe.id.to_string()));
+
+            // Terms only the document itself contains cannot relate it to others
+            let terms: Vec<&str> = body["terms"].as_array().unwrap().iter().map(|term| term.as_str().unwrap()).collect();
+            assert!(terms.iter().any(|term| term.starts_with("globex")));
+            assert!(!terms.iter().any(|term| term.starts_with("termin")));
+            let shared_terms = body["documents"][0]["shared_terms"].as_array().unwrap();
+            assert!(shared_terms.len() > body["documents"][1]["shared_terms"].as_array().unwrap().len());
+
+            let (status, body) = get(&ctx.app, &format!("/api/documents/{}/similar?limit=1", contract.id), &token).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(ids(&body), [amendment.id.to_string()]);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_similar_documents_stay_within_what_the_user_can_see() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let db = &ctx.state.db;
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let alice = auth_helper.create_test_user().await;
+            let alice_token = auth_helper.login_user(&alice.username, &alice.password).await;
+            let bob = auth_helper.create_test_user().await;
+            let bob_token = auth_helper.login_user(&bob.username, &bob.password).await;
+
+            let text = "Service agreement between Initech and Globex covering maintenance and warranty";
+            let contract = db.create_document(document(alice.user_response.id, "contract.pdf", text)).await?;
+            db.create_document(document(bob.user_response.id, "contract.pdf", text)).await?;
+
+            let (status, body) = get(&ctx.app, &format!("/api/documents/{}/similar", contract.id), &alice_token).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(ids(&body).is_empty());
+
+            // Another user's document, or one that does not exist
+            let (status, _) = get(&ctx.app, &format!("/api/documents/{}/similar", contract.id), &bob_token).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            let (status, _) = get(&ctx.app, &format!("/api/documents/{}/similar", Uuid::new_v4()), &alice_token).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+}
@@ -252,6 +252,7 @@ fn test_auto_sync_configuration() {
         auto_sync: true,
         sync_interval_minutes: 60,
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     };
     
     assert!(webdav_config.auto_sync);
@@ -267,6 +268,7 @@ fn test_auto_sync_configuration() {
         auto_sync: false,
         sync_interval_minutes: 60,
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     };
     
     assert!(!webdav_disabled.auto_sync);
@@ -328,6 +330,7 @@ fn test_source_configuration_sizes() {
         auto_sync: true,
         sync_interval_minutes: 60,
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     };
     
     let serialized = serde_json::to_string(&webdav_config).unwrap();
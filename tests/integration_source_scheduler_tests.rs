@@ -72,6 +72,12 @@ impl MockDatabase {
                     "sync_interval_minutes": 120
                 });
             }
+            SourceType::Imap | SourceType::Dropbox | SourceType::OneDrive | SourceType::Sftp => {
+                config = json!({
+                    "auto_sync": true,
+                    "sync_interval_minutes": 60
+                });
+            }
         }
 
         self.sources.push(Source {
@@ -134,6 +140,12 @@ impl MockDatabase {
                     "sync_interval_minutes": 30
                 });
             }
+            SourceType::Imap | SourceType::Dropbox | SourceType::OneDrive | SourceType::Sftp => {
+                config = json!({
+                    "auto_sync": true,
+                    "sync_interval_minutes": 60
+                });
+            }
         }
 
         self.sources.push(Source {
@@ -648,6 +660,7 @@ async fn test_config_validation() {
         auto_sync: true,
         sync_interval_minutes: 60,
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     };
     
     assert!(!webdav_config.server_url.is_empty());
@@ -661,6 +674,7 @@ async fn test_config_validation() {
         watch_folders: vec!["/test/path".to_string()],
         recursive: true,
         follow_symlinks: false,
+        watch_mode: Default::default(),
         auto_sync: true,
         sync_interval_minutes: 30,
         file_extensions: vec![".pdf".to_string()],
@@ -680,6 +694,7 @@ async fn test_config_validation() {
         watch_folders: vec!["docs/".to_string()],
         auto_sync: true,
         sync_interval_minutes: 120,
+        notifications: None,
         file_extensions: vec![".pdf".to_string()],
     };
     
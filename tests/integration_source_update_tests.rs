@@ -148,6 +148,26 @@ fn test_config_validation_for_type() {
                     serde_json::from_value(config.clone()).map_err(|_| "Invalid S3 configuration")?;
                 Ok(())
             }
+            SourceType::Imap => {
+                let _: readur::models::ImapSourceConfig =
+                    serde_json::from_value(config.clone()).map_err(|_| "Invalid IMAP configuration")?;
+                Ok(())
+            }
+            SourceType::Dropbox => {
+                let _: readur::models::DropboxSourceConfig =
+                    serde_json::from_value(config.clone()).map_err(|_| "Invalid Dropbox configuration")?;
+                Ok(())
+            }
+            SourceType::OneDrive => {
+                let _: readur::models::OneDriveSourceConfig =
+                    serde_json::from_value(config.clone()).map_err(|_| "Invalid OneDrive configuration")?;
+                Ok(())
+            }
+            SourceType::Sftp => {
+                let _: readur::models::SftpSourceConfig =
+                    serde_json::from_value(config.clone()).map_err(|_| "Invalid SFTP configuration")?;
+                Ok(())
+            }
         }
     }
 
@@ -549,6 +549,7 @@ fn get_source_priority(source_type: &SourceType) -> u32 {
         SourceType::LocalFolder => 1, // Highest priority
         SourceType::WebDAV => 2,      // Medium priority
         SourceType::S3 => 3,          // Lower priority
+        SourceType::Imap | SourceType::Dropbox | SourceType::OneDrive | SourceType::Sftp => 3,
     }
 }
 
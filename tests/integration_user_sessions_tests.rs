@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum::Router;
+    use readur::test_utils::{TestAuthHelper, TestContext};
+    use serde_json::Value;
+    use tower::util::ServiceExt;
+    use uuid::Uuid;
+
+    async fn request(app: &Router, method: &str, uri: &str, token: &str) -> (StatusCode, Value) {
+        let response = app
+            .clone()
+            .oneshot(
+                axum::http::Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    async fn session_ids(app: &Router, token: &str) -> Vec<(Uuid, bool)> {
+        let (status, sessions) = request(app, "GET", "/api/users/me/sessions", token).await;
+        assert_eq!(status, StatusCode::OK);
+        sessions
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|session| {
+                (
+                    session["id"].as_str().unwrap().parse().unwrap(),
+                    session["current"].as_bool().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_list_and_revoke_own_sessions() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let user = auth_helper.create_test_user().await;
+            let laptop = auth_helper.login_user(&user.username, &user.password).await;
+            let phone = auth_helper.login_user(&user.username, &user.password).await;
+
+            let sessions = session_ids(&ctx.app, &laptop).await;
+            assert_eq!(sessions.len(), 2);
+            assert_eq!(sessions.iter().filter(|(_, current)| *current).count(), 1);
+            let laptop_session = sessions.iter().find(|(_, current)| *current).unwrap().0;
+            let phone_session = sessions.iter().find(|(_, current)| !*current).unwrap().0;
+
+            // Ending the phone's session logs the phone out but not the laptop
+            let uri = format!("/api/users/me/sessions/{}", phone_session);
+            let (status, _) = request(&ctx.app, "DELETE", &uri, &laptop).await;
+            assert_eq!(status, StatusCode::NO_CONTENT);
+            let (status, _) = request(&ctx.app, "GET", "/api/users/me/sessions", &phone).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(session_ids(&ctx.app, &laptop).await, vec![(laptop_session, true)]);
+
+            // An ended session cannot be ended again
+            let (status, _) = request(&ctx.app, "DELETE", &uri, &laptop).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+
+            // Logging out ends the session making the request
+            let (status, _) = request(&ctx.app, "POST", "/api/auth/logout", &laptop).await;
+            assert_eq!(status, StatusCode::NO_CONTENT);
+            let (status, _) = request(&ctx.app, "GET", "/api/users/me/sessions", &laptop).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sessions_of_other_users_cannot_be_revoked() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let alice = auth_helper.create_test_user().await;
+            let bob = auth_helper.create_test_user().await;
+            let alice_token = auth_helper.login_user(&alice.username, &alice.password).await;
+            let bob_token = auth_helper.login_user(&bob.username, &bob.password).await;
+            let (bob_session, _) = session_ids(&ctx.app, &bob_token).await[0];
+
+            let uri = format!("/api/users/me/sessions/{}", bob_session);
+            let (status, _) = request(&ctx.app, "DELETE", &uri, &alice_token).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+
+            // Only admins may log other users out
+            let uri = format!("/api/users/{}/sessions", bob.id());
+            let (status, _) = request(&ctx.app, "DELETE", &uri, &alice_token).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+
+            let (status, _) = request(&ctx.app, "GET", "/api/users/me/sessions", &bob_token).await;
+            assert_eq!(status, StatusCode::OK);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_revoke_other_sessions_keeps_the_current_one() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let user = auth_helper.create_test_user().await;
+            let current = auth_helper.login_user(&user.username, &user.password).await;
+            let others = [
+                auth_helper.login_user(&user.username, &user.password).await,
+                auth_helper.login_user(&user.username, &user.password).await,
+            ];
+
+            let (status, body) = request(&ctx.app, "DELETE", "/api/users/me/sessions", &current).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["revoked"], 2);
+
+            for token in &others {
+                let (status, _) = request(&ctx.app, "GET", "/api/users/me/sessions", token).await;
+                assert_eq!(status, StatusCode::UNAUTHORIZED);
+            }
+            let sessions = session_ids(&ctx.app, &current).await;
+            assert_eq!(sessions.len(), 1);
+            assert!(sessions[0].1);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_admin_force_logout() {
+        let ctx = TestContext::new().await;
+
+        let result: Result<()> = async {
+            let auth_helper = TestAuthHelper::new(ctx.app.clone());
+            let admin = auth_helper.create_admin_user().await;
+            let admin_token = auth_helper.login_user(&admin.username, &admin.password).await;
+            let user = auth_helper.create_test_user().await;
+            let tokens = [
+                auth_helper.login_user(&user.username, &user.password).await,
+                auth_helper.login_user(&user.username, &user.password).await,
+            ];
+
+            let uri = format!("/api/users/{}/sessions", user.id());
+            let (status, body) = request(&ctx.app, "DELETE", &uri, &admin_token).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["revoked"], 2);
+
+            for token in &tokens {
+                let (status, _) = request(&ctx.app, "GET", "/api/users/me/sessions", token).await;
+                assert_eq!(status, StatusCode::UNAUTHORIZED);
+            }
+            // The user can log in again afterwards, and the admin stays logged in
+            let token = auth_helper.login_user(&user.username, &user.password).await;
+            assert_eq!(session_ids(&ctx.app, &token).await.len(), 1);
+            let (status, _) = request(&ctx.app, "GET", "/api/users/me/sessions", &admin_token).await;
+            assert_eq!(status, StatusCode::OK);
+
+            Ok(())
+        }.await;
+
+        if let Err(e) = ctx.cleanup_and_close().await {
+            eprintln!("Warning: Test cleanup failed: {}", e);
+        }
+
+        result.unwrap();
+    }
+}
@@ -39,6 +39,7 @@ async fn create_test_webdav_source(
         auto_sync,
         sync_interval_minutes: 1, // Fast interval for testing
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     };
 
     let create_source = CreateSource {
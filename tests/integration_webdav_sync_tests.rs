@@ -44,6 +44,7 @@ fn create_test_source_config() -> WebDAVSourceConfig {
         auto_sync: true,
         sync_interval_minutes: 60,
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     }
 }
 
@@ -26,6 +26,7 @@ async fn test_s3_service_new_validation() {
         file_extensions: vec![],
         auto_sync: false,
         sync_interval_minutes: 0,
+        notifications: None,
     };
 
     let result = S3Service::new(config).await;
@@ -59,6 +60,7 @@ async fn test_s3_service_configuration() {
         file_extensions: vec!["pdf".to_string(), "txt".to_string()],
         auto_sync: false,
         sync_interval_minutes: 60,
+        notifications: None,
     };
     
     // This test verifies the configuration structure is correct
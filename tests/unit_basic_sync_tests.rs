@@ -35,6 +35,7 @@ fn test_webdav_config_serialization() {
         auto_sync: true,
         sync_interval_minutes: 60,
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     };
     
     let json_value = serde_json::to_value(&config).unwrap();
@@ -55,6 +56,7 @@ fn test_local_folder_config_serialization() {
         sync_interval_minutes: 30,
         recursive: true,
         follow_symlinks: false,
+        watch_mode: Default::default(),
     };
     
     let json_value = serde_json::to_value(&config).unwrap();
@@ -79,6 +81,7 @@ fn test_s3_config_serialization() {
         file_extensions: vec![".pdf".to_string(), ".docx".to_string()],
         auto_sync: true,
         sync_interval_minutes: 120,
+        notifications: None,
     };
     
     let json_value = serde_json::to_value(&config).unwrap();
@@ -106,6 +109,7 @@ fn test_auto_sync_validation() {
             auto_sync: true,
             sync_interval_minutes: interval,
             server_type: Some("nextcloud".to_string()),
+            sync_direction: Default::default(),
         };
         
         assert!(webdav_config.auto_sync);
@@ -134,6 +138,7 @@ fn test_file_extension_validation() {
         auto_sync: true,
         sync_interval_minutes: 60,
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     };
     
     for ext in &config.file_extensions {
@@ -155,6 +160,7 @@ fn test_watch_folder_validation() {
             sync_interval_minutes: 30,
             recursive: true,
             follow_symlinks: false,
+            watch_mode: Default::default(),
         };
         
         assert_eq!(config.watch_folders[0], folder);
@@ -183,6 +189,7 @@ fn test_server_type_validation() {
             auto_sync: true,
             sync_interval_minutes: 60,
             server_type: server_type.clone(),
+            sync_direction: Default::default(),
         };
         
         assert_eq!(config.server_type, server_type);
@@ -217,6 +224,7 @@ fn test_s3_bucket_name_validation() {
             file_extensions: vec![".pdf".to_string()],
             auto_sync: true,
             sync_interval_minutes: 120,
+            notifications: None,
         };
         
         assert_eq!(config.bucket_name, bucket_name);
@@ -240,6 +248,7 @@ fn test_endpoint_url_handling() {
         file_extensions: vec![".pdf".to_string()],
         auto_sync: true,
         sync_interval_minutes: 120,
+        notifications: None,
     };
     
     assert!(aws_config.endpoint_url.is_none());
@@ -256,6 +265,7 @@ fn test_endpoint_url_handling() {
         file_extensions: vec![".pdf".to_string()],
         auto_sync: true,
         sync_interval_minutes: 120,
+        notifications: None,
     };
     
     assert!(minio_config.endpoint_url.is_some());
@@ -287,6 +297,7 @@ fn test_sync_interval_ranges() {
             auto_sync: true,
             sync_interval_minutes: interval,
             server_type: Some("nextcloud".to_string()),
+            sync_direction: Default::default(),
         };
         
         assert_eq!(config.sync_interval_minutes, interval);
@@ -316,6 +327,7 @@ fn test_configuration_size_limits() {
         auto_sync: true,
         sync_interval_minutes: 60,
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     };
     
     let serialized = serde_json::to_string(&large_webdav_config).unwrap();
@@ -342,6 +354,7 @@ fn test_concurrent_configuration_access() {
         auto_sync: true,
         sync_interval_minutes: 60,
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     });
     
     let mut handles = vec![];
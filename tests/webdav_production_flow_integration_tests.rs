@@ -47,6 +47,7 @@ async fn create_production_webdav_source(
         auto_sync,
         sync_interval_minutes: 5, // Realistic interval
         server_type: Some("nextcloud".to_string()),
+        sync_direction: Default::default(),
     };
 
     let create_source = CreateSource {